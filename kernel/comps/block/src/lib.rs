@@ -40,13 +40,15 @@ pub mod id;
 mod impl_block_device;
 mod prelude;
 pub mod request_queue;
+pub mod verity;
 
 use component::{init_component, ComponentInitError};
-use ostd::sync::SpinLock;
+use ostd::{mm::UFrame, sync::SpinLock};
 use spin::Once;
 
 use self::{
     bio::{BioEnqueueError, SubmittedBio},
+    id::Bid,
     prelude::*,
 };
 
@@ -59,6 +61,17 @@ pub trait BlockDevice: Send + Sync + Any + Debug {
 
     /// Returns the metadata of the block device.
     fn metadata(&self) -> BlockDeviceMeta;
+
+    /// Returns the frame that backs the block `bid`, if the device supports direct access.
+    ///
+    /// Devices such as persistent memory are addressable like the main memory.
+    /// The returned frame can be mapped into user space or accessed by the CPU directly,
+    /// whose writes are persisted after a [`BioType::Flush`] request completes.
+    ///
+    /// [`BioType::Flush`]: crate::bio::BioType::Flush
+    fn direct_access(&self, _bid: Bid) -> Option<UFrame> {
+        None
+    }
 }
 
 /// Metadata for a block device.
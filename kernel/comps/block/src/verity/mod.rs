@@ -0,0 +1,419 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! A read-only block device whose blocks are verified against a hash tree at read time.
+//!
+//! The device is compatible with the `verity` target of the device mapper in Linux. The data
+//! blocks are stored on a data device, and the hash tree is stored on a separate hash device
+//! in the format of `veritysetup format`, i.e., a superblock followed by the levels of the
+//! hash tree from the top to the bottom. Each hash block holds the digests of the blocks in
+//! the level below, and the digest of the top hash block is the root hash.
+//!
+//! The root hash is trusted, so it must be provided by a trusted source (e.g., the kernel
+//! command line). Every block that is read is verified against the root hash, so any
+//! modification to the data device or the hash device is detected. The reads of the
+//! corrupted blocks fail with I/O errors.
+//!
+//! Only SHA-256 and blocks of [`BLOCK_SIZE`] are supported.
+
+mod sha256;
+
+use ostd::{mm::VmIo, sync::SpinLock, Pod};
+
+use self::sha256::{Sha256, DIGEST_SIZE};
+use crate::{
+    bio::{BioEnqueueError, BioStatus, BioType, SubmittedBio},
+    id::Bid,
+    prelude::*,
+    BlockDevice, BlockDeviceMeta, BLOCK_SIZE, SECTOR_SIZE,
+};
+
+/// The number of digests in a hash block, in bits.
+const HASHES_PER_BLOCK_BITS: u32 = (BLOCK_SIZE / DIGEST_SIZE).ilog2();
+
+/// The maximal number of the verified hash blocks that are cached.
+const MAX_CACHED_HASH_BLOCKS: usize = 1024;
+
+/// The error type returned when opening or reading a [`VerityDevice`].
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum VerityError {
+    /// An I/O error occurred on the data device or the hash device
+    IoError,
+    /// The superblock on the hash device is invalid
+    InvalidSuperblock,
+    /// The parameters of the hash tree are not supported
+    NotSupported,
+    /// A block does not match the hash tree
+    Corrupted,
+}
+
+impl From<ostd::Error> for VerityError {
+    fn from(_error: ostd::Error) -> Self {
+        Self::IoError
+    }
+}
+
+/// A block device verified by a hash tree.
+pub struct VerityDevice {
+    data_device: Arc<dyn BlockDevice>,
+    hash_device: Arc<dyn BlockDevice>,
+    data_blocks: u64,
+    salt: Vec<u8>,
+    root_digest: [u8; DIGEST_SIZE],
+    /// The index of the first hash block of each level, where level 0 is the bottom level.
+    level_starts: Vec<u64>,
+    /// The hash blocks that have been verified, indexed by their block indexes.
+    verified_hash_blocks: SpinLock<BTreeMap<u64, Arc<[u8]>>>,
+}
+
+impl VerityDevice {
+    /// Opens a device whose data blocks are on `data_device`, and whose hash tree is on
+    /// `hash_device` with the root hash `root_digest`.
+    ///
+    /// The top hash block is verified against the root hash, so a wrong root hash is
+    /// detected immediately.
+    pub fn open(
+        data_device: Arc<dyn BlockDevice>,
+        hash_device: Arc<dyn BlockDevice>,
+        root_digest: &[u8],
+    ) -> Result<Self, VerityError> {
+        let superblock: VeritySuperblock = hash_device.read_val(0)?;
+        superblock.check()?;
+        let root_digest = root_digest
+            .try_into()
+            .map_err(|_| VerityError::NotSupported)?;
+
+        let data_blocks = superblock.data_blocks;
+        if data_blocks
+            .checked_mul(BLOCK_SIZE as u64)
+            .map_or(true, |size| size > device_size(data_device.as_ref()))
+        {
+            return Err(VerityError::InvalidSuperblock);
+        }
+
+        // Like Linux, there are as many levels as needed to reduce the digests of the data
+        // blocks to a single hash block, and the levels are stored from the top to the bottom
+        // after the superblock.
+        let mut levels = 0;
+        while HASHES_PER_BLOCK_BITS * levels < u64::BITS
+            && (data_blocks - 1) >> (HASHES_PER_BLOCK_BITS * levels) != 0
+        {
+            levels += 1;
+        }
+        let mut level_starts = vec![0; levels as usize];
+        let mut hash_position = 1;
+        for level in (0..levels).rev() {
+            level_starts[level as usize] = hash_position;
+            hash_position += data_blocks.div_ceil(1 << ((level + 1) * HASHES_PER_BLOCK_BITS));
+        }
+        if hash_position * BLOCK_SIZE as u64 > device_size(hash_device.as_ref()) {
+            return Err(VerityError::InvalidSuperblock);
+        }
+
+        let device = Self {
+            data_device,
+            hash_device,
+            data_blocks,
+            salt: superblock.salt[..superblock.salt_size as usize].to_vec(),
+            root_digest,
+            level_starts,
+            verified_hash_blocks: SpinLock::new(BTreeMap::new()),
+        };
+        if let Some(top_start) = device.level_starts.last() {
+            device.read_hash_block(*top_start, &device.root_digest)?;
+        }
+        Ok(device)
+    }
+
+    /// Reads the data blocks starting from `bid` and verifies them.
+    fn read_verified_blocks(&self, bid: u64, buf: &mut [u8]) -> Result<(), VerityError> {
+        let nblocks = (buf.len() / BLOCK_SIZE) as u64;
+        if bid + nblocks > self.data_blocks {
+            return Err(VerityError::IoError);
+        }
+
+        self.data_device
+            .read_bytes(Bid::new(bid).to_offset(), buf)?;
+        for (data_bid, block) in (bid..bid + nblocks).zip(buf.chunks_exact(BLOCK_SIZE)) {
+            let expected_digest = self.data_block_digest(data_bid)?;
+            if self.digest(block) != expected_digest {
+                log::error!("[Verity] data block {} is corrupted", data_bid);
+                return Err(VerityError::Corrupted);
+            }
+        }
+        Ok(())
+    }
+
+    /// Returns the digest of the data block in the hash tree, which is verified by walking
+    /// the hash tree from the top.
+    fn data_block_digest(&self, bid: u64) -> Result<[u8; DIGEST_SIZE], VerityError> {
+        let mut digest = self.root_digest;
+        for (level, level_start) in self.level_starts.iter().enumerate().rev() {
+            let position = bid >> (level as u32 * HASHES_PER_BLOCK_BITS);
+            let hash_bid = level_start + (position >> HASHES_PER_BLOCK_BITS);
+            let offset = (position as usize & ((1 << HASHES_PER_BLOCK_BITS) - 1)) * DIGEST_SIZE;
+
+            let hash_block = self.read_hash_block(hash_bid, &digest)?;
+            digest.copy_from_slice(&hash_block[offset..offset + DIGEST_SIZE]);
+        }
+        Ok(digest)
+    }
+
+    /// Reads the hash block and verifies it against `expected_digest`, unless it has been
+    /// verified before.
+    fn read_hash_block(
+        &self,
+        bid: u64,
+        expected_digest: &[u8; DIGEST_SIZE],
+    ) -> Result<Arc<[u8]>, VerityError> {
+        if let Some(block) = self.verified_hash_blocks.lock().get(&bid) {
+            return Ok(block.clone());
+        }
+
+        let mut block = vec![0u8; BLOCK_SIZE];
+        self.hash_device
+            .read_bytes(Bid::new(bid).to_offset(), &mut block)?;
+        if self.digest(&block) != *expected_digest {
+            log::error!("[Verity] hash block {} is corrupted", bid);
+            return Err(VerityError::Corrupted);
+        }
+
+        let block: Arc<[u8]> = block.into();
+        let mut verified_hash_blocks = self.verified_hash_blocks.lock();
+        if verified_hash_blocks.len() >= MAX_CACHED_HASH_BLOCKS {
+            verified_hash_blocks.pop_first();
+        }
+        verified_hash_blocks.insert(bid, block.clone());
+        Ok(block)
+    }
+
+    /// Computes the digest of a block, which is salted like the format version 1 of Linux.
+    fn digest(&self, block: &[u8]) -> [u8; DIGEST_SIZE] {
+        let mut hasher = Sha256::new();
+        hasher.update(&self.salt);
+        hasher.update(block);
+        hasher.finalize()
+    }
+
+    fn handle_read_bio(&self, bio: &SubmittedBio) -> BioStatus {
+        let start_offset = bio.sid_range().start.to_offset();
+        let end_offset = bio.sid_range().end.to_offset();
+        let start_bid = Bid::from_offset(start_offset).to_raw();
+        let end_bid = end_offset.div_ceil(BLOCK_SIZE) as u64;
+
+        let mut buf = vec![0u8; (end_bid - start_bid) as usize * BLOCK_SIZE];
+        if self.read_verified_blocks(start_bid, &mut buf).is_err() {
+            return BioStatus::IoError;
+        }
+
+        let mut base = start_offset % BLOCK_SIZE;
+        for segment in bio.segments() {
+            let nbytes = segment.nbytes();
+            if segment.write_bytes(0, &buf[base..base + nbytes]).is_err() {
+                return BioStatus::IoError;
+            }
+            base += nbytes;
+        }
+        BioStatus::Complete
+    }
+}
+
+impl BlockDevice for VerityDevice {
+    fn enqueue(&self, bio: SubmittedBio) -> Result<(), BioEnqueueError> {
+        let status = match bio.type_() {
+            BioType::Read => self.handle_read_bio(&bio),
+            // Like Linux, the device is read-only.
+            BioType::Write => BioStatus::IoError,
+            BioType::Flush => BioStatus::Complete,
+            BioType::Discard => BioStatus::NotSupported,
+        };
+        bio.complete(status);
+        Ok(())
+    }
+
+    fn metadata(&self) -> BlockDeviceMeta {
+        BlockDeviceMeta {
+            max_nr_segments_per_bio: usize::MAX,
+            nr_sectors: self.data_blocks as usize * (BLOCK_SIZE / SECTOR_SIZE),
+        }
+    }
+}
+
+impl Debug for VerityDevice {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("VerityDevice")
+            .field("data_device", &self.data_device)
+            .field("hash_device", &self.hash_device)
+            .field("data_blocks", &self.data_blocks)
+            .finish_non_exhaustive()
+    }
+}
+
+fn device_size(device: &dyn BlockDevice) -> u64 {
+    device.metadata().nr_sectors as u64 * SECTOR_SIZE as u64
+}
+
+/// The superblock on the hash device (`struct verity_sb` of `veritysetup`).
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Pod)]
+struct VeritySuperblock {
+    signature: [u8; 8],
+    version: u32,
+    hash_type: u32,
+    uuid: [u8; 16],
+    algorithm: [u8; 32],
+    data_block_size: u32,
+    hash_block_size: u32,
+    data_blocks: u64,
+    salt_size: u16,
+    _pad1: [u8; 6],
+    salt: [u8; 256],
+    _pad2: [u8; 168],
+}
+
+impl VeritySuperblock {
+    const SIGNATURE: [u8; 8] = *b"verity\0\0";
+    const VERSION: u32 = 1;
+    /// The hash type where the salt is prepended to the blocks.
+    const HASH_TYPE: u32 = 1;
+    const ALGORITHM: &'static [u8] = b"sha256";
+
+    fn check(&self) -> Result<(), VerityError> {
+        if self.signature != Self::SIGNATURE
+            || self.version != Self::VERSION
+            || self.salt_size as usize > self.salt.len()
+            || self.data_blocks == 0
+        {
+            return Err(VerityError::InvalidSuperblock);
+        }
+
+        let algorithm_len = self
+            .algorithm
+            .iter()
+            .position(|byte| *byte == 0)
+            .unwrap_or(self.algorithm.len());
+        if self.hash_type != Self::HASH_TYPE
+            || &self.algorithm[..algorithm_len] != Self::ALGORITHM
+            || self.data_block_size as usize != BLOCK_SIZE
+            || self.hash_block_size as usize != BLOCK_SIZE
+        {
+            return Err(VerityError::NotSupported);
+        }
+        Ok(())
+    }
+}
+
+#[cfg(ktest)]
+mod test {
+    use ostd::prelude::ktest;
+
+    use super::*;
+
+    /// A block device in memory.
+    #[derive(Debug)]
+    struct MemoryDisk(SpinLock<Vec<u8>>);
+
+    impl MemoryDisk {
+        fn new(data: Vec<u8>) -> Arc<Self> {
+            Arc::new(Self(SpinLock::new(data)))
+        }
+    }
+
+    impl BlockDevice for MemoryDisk {
+        fn enqueue(&self, bio: SubmittedBio) -> Result<(), BioEnqueueError> {
+            let mut data = self.0.lock();
+            let mut offset = bio.sid_range().start.to_offset();
+            let mut status = BioStatus::Complete;
+            for segment in bio.segments() {
+                let range = offset..offset + segment.nbytes();
+                let res = match bio.type_() {
+                    BioType::Read => segment.write_bytes(0, &data[range]),
+                    BioType::Write => segment.read_bytes(0, &mut data[range]),
+                    _ => Ok(()),
+                };
+                if res.is_err() {
+                    status = BioStatus::IoError;
+                }
+                offset += segment.nbytes();
+            }
+            drop(data);
+            bio.complete(status);
+            Ok(())
+        }
+
+        fn metadata(&self) -> BlockDeviceMeta {
+            BlockDeviceMeta {
+                max_nr_segments_per_bio: usize::MAX,
+                nr_sectors: self.0.lock().len() / SECTOR_SIZE,
+            }
+        }
+    }
+
+    const SALT: &[u8] = b"salt";
+    const DATA_BLOCKS: usize = 2;
+
+    fn salted_digest(block: &[u8]) -> [u8; DIGEST_SIZE] {
+        let mut hasher = Sha256::new();
+        hasher.update(SALT);
+        hasher.update(block);
+        hasher.finalize()
+    }
+
+    /// Builds the data device and the hash device with a single-level hash tree, and returns
+    /// them along with the root hash.
+    fn build_devices() -> (Arc<MemoryDisk>, Arc<MemoryDisk>, [u8; DIGEST_SIZE]) {
+        let data: Vec<u8> = (0..DATA_BLOCKS * BLOCK_SIZE)
+            .map(|i| (i % 251) as u8)
+            .collect();
+
+        let mut superblock = VeritySuperblock::new_zeroed();
+        superblock.signature = VeritySuperblock::SIGNATURE;
+        superblock.version = VeritySuperblock::VERSION;
+        superblock.hash_type = VeritySuperblock::HASH_TYPE;
+        superblock.algorithm[..VeritySuperblock::ALGORITHM.len()]
+            .copy_from_slice(VeritySuperblock::ALGORITHM);
+        superblock.data_block_size = BLOCK_SIZE as u32;
+        superblock.hash_block_size = BLOCK_SIZE as u32;
+        superblock.data_blocks = DATA_BLOCKS as u64;
+        superblock.salt_size = SALT.len() as u16;
+        superblock.salt[..SALT.len()].copy_from_slice(SALT);
+
+        let mut hash = vec![0u8; 2 * BLOCK_SIZE];
+        hash[..size_of::<VeritySuperblock>()].copy_from_slice(superblock.as_bytes());
+        for (i, block) in data.chunks_exact(BLOCK_SIZE).enumerate() {
+            let offset = BLOCK_SIZE + i * DIGEST_SIZE;
+            hash[offset..offset + DIGEST_SIZE].copy_from_slice(&salted_digest(block));
+        }
+        let root_digest = salted_digest(&hash[BLOCK_SIZE..]);
+
+        (MemoryDisk::new(data), MemoryDisk::new(hash), root_digest)
+    }
+
+    #[ktest]
+    fn read_verified_and_corrupted_blocks() {
+        let (data_device, hash_device, root_digest) = build_devices();
+        let device: Arc<dyn BlockDevice> =
+            Arc::new(VerityDevice::open(data_device.clone(), hash_device, &root_digest).unwrap());
+
+        let mut buf = vec![0u8; BLOCK_SIZE];
+        device.read_bytes(BLOCK_SIZE, &mut buf).unwrap();
+        assert_eq!(buf, data_device.0.lock()[BLOCK_SIZE..]);
+
+        data_device.0.lock()[BLOCK_SIZE + 42] ^= 1;
+        assert!(matches!(
+            device.read_bytes(BLOCK_SIZE, &mut buf),
+            Err(ostd::Error::IoError)
+        ));
+        // The other block is still readable.
+        device.read_bytes(0, &mut buf).unwrap();
+    }
+
+    #[ktest]
+    fn wrong_root_hash() {
+        let (data_device, hash_device, mut root_digest) = build_devices();
+        root_digest[0] ^= 1;
+        assert_eq!(
+            VerityDevice::open(data_device, hash_device, &root_digest).unwrap_err(),
+            VerityError::Corrupted
+        );
+    }
+}
@@ -118,6 +118,8 @@ pub enum Key {
     Insert = 110,
     Delete = 111,
 
+    Power = 116,
+
     LeftMeta = 125,
 }
 
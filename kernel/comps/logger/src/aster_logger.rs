@@ -1,19 +1,69 @@
 // SPDX-License-Identifier: MPL-2.0
 
-use log::{Metadata, Record};
+use core::sync::atomic::{AtomicU8, Ordering};
+
+use log::{LevelFilter, Metadata, Record};
 use ostd::timer::Jiffies;
 
+use crate::{filter, kmsg};
+
 /// The logger used for Asterinas.
 struct AsterLogger;
 
 static LOGGER: AsterLogger = AsterLogger;
 
+/// The console log level, which is the same as Linux.
+///
+/// The messages whose syslog levels are less than it are printed to the console.
+static CONSOLE_LOGLEVEL: AtomicU8 = AtomicU8::new(DEFAULT_CONSOLE_LOGLEVEL);
+
+/// The minimum console log level, with which only the emergency messages are printed.
+pub const MIN_CONSOLE_LOGLEVEL: u8 = 1;
+
+/// The maximum console log level, with which all messages are printed.
+pub const MAX_CONSOLE_LOGLEVEL: u8 = 8;
+
+const DEFAULT_CONSOLE_LOGLEVEL: u8 = 7;
+
+/// Returns the console log level.
+pub fn console_loglevel() -> u8 {
+    CONSOLE_LOGLEVEL.load(Ordering::Relaxed)
+}
+
+/// Sets the console log level.
+///
+/// The level is clamped to [`MIN_CONSOLE_LOGLEVEL`] and [`MAX_CONSOLE_LOGLEVEL`].
+pub fn set_console_loglevel(level: u8) {
+    CONSOLE_LOGLEVEL.store(
+        level.clamp(MIN_CONSOLE_LOGLEVEL, MAX_CONSOLE_LOGLEVEL),
+        Ordering::Relaxed,
+    );
+}
+
+/// Logs a message written by a user program, e.g., via `/dev/kmsg`.
+pub fn log_user_message(prio: u8, text: &str) {
+    kmsg::append(prio, "", format_args!("{}", text));
+    if prio & 0x7 < console_loglevel() {
+        super::_print(format_args!("{}\n", text.trim_end_matches('\n')));
+    }
+}
+
 impl log::Log for AsterLogger {
-    fn enabled(&self, _metadata: &Metadata) -> bool {
-        true
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        filter::is_enabled(metadata)
     }
 
     fn log(&self, record: &Record) {
+        if !filter::is_enabled(record.metadata()) {
+            return;
+        }
+
+        let level = kmsg::syslog_level(record.level());
+        kmsg::append(kmsg::LOG_KERN | level, record.target(), *record.args());
+        if level >= console_loglevel() {
+            return;
+        }
+
         let timestamp = Jiffies::elapsed().as_duration().as_secs_f64();
 
         // Use a global lock to prevent interleaving of log messages.
@@ -60,5 +110,18 @@ fn print_logs(record: &Record, timestamp: f64) {
 }
 
 pub(super) fn init() {
+    // The level set by OSTD, which is specified in the kernel command line, decides what
+    // messages are printed to the console. More messages are kept in the kernel message
+    // buffer, so that they can be read by the log collectors.
+    let console_level = log::max_level();
+    set_console_loglevel(match console_level {
+        LevelFilter::Off => MIN_CONSOLE_LOGLEVEL,
+        LevelFilter::Error => 4,
+        LevelFilter::Warn => 5,
+        LevelFilter::Info => 7,
+        LevelFilter::Debug | LevelFilter::Trace => MAX_CONSOLE_LOGLEVEL,
+    });
+    filter::init(console_level.max(LevelFilter::Info));
+
     ostd::logger::inject_logger(&LOGGER);
 }
@@ -0,0 +1,166 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! The runtime filter of the log messages.
+//!
+//! The filter is specified with the same syntax as `env_logger`, i.e., a comma-separated list
+//! of directives. A directive is either `LEVEL`, which sets the default level, or
+//! `TARGET=LEVEL`, which sets the level of the messages whose target is `TARGET` or is inside
+//! the module `TARGET`. Since the target of a message is the module path where it is logged
+//! unless specified otherwise, the targets serve as the subsystem tags of the messages. If
+//! multiple directives match a target, the one with the longest target wins.
+//!
+//! For example, `warn,aster_nix::fs=debug,aster_virtio=off` logs the debug messages of the
+//! file systems, turns off the messages of the VirtIO drivers, and logs the warnings of the
+//! others.
+
+use alloc::{
+    string::{String, ToString},
+    vec::Vec,
+};
+use core::{fmt::Write, str::FromStr};
+
+use log::{LevelFilter, Metadata};
+use ostd::sync::{LocalIrqDisabled, SpinLock};
+
+static FILTER: SpinLock<Filter, LocalIrqDisabled> = SpinLock::new(Filter::new());
+
+struct Filter {
+    default: LevelFilter,
+    directives: Vec<Directive>,
+}
+
+struct Directive {
+    target: String,
+    level: LevelFilter,
+}
+
+/// An error that indicates that a filter specification is invalid.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct InvalidSpecError;
+
+impl Filter {
+    const fn new() -> Self {
+        Self {
+            default: LevelFilter::Info,
+            directives: Vec::new(),
+        }
+    }
+
+    fn level_of(&self, target: &str) -> LevelFilter {
+        self.directives
+            .iter()
+            .filter(|directive| is_in_module(target, &directive.target))
+            .max_by_key(|directive| directive.target.len())
+            .map_or(self.default, |directive| directive.level)
+    }
+
+    fn max_level(&self) -> LevelFilter {
+        self.directives
+            .iter()
+            .map(|directive| directive.level)
+            .fold(self.default, LevelFilter::max)
+    }
+}
+
+/// Returns whether the target is the module or is inside the module.
+fn is_in_module(target: &str, module: &str) -> bool {
+    target
+        .strip_prefix(module)
+        .is_some_and(|rest| rest.is_empty() || rest.starts_with("::"))
+}
+
+/// Initializes the filter with the default level.
+pub(crate) fn init(default: LevelFilter) {
+    let mut filter = FILTER.lock();
+    filter.default = default;
+    log::set_max_level(filter.max_level());
+}
+
+/// Returns whether the message with the metadata should be logged.
+pub fn is_enabled(metadata: &Metadata) -> bool {
+    metadata.level() <= FILTER.lock().level_of(metadata.target())
+}
+
+/// Returns the specification of the filter.
+pub fn spec() -> String {
+    let filter = FILTER.lock();
+    let mut spec = filter.default.as_str().to_ascii_lowercase();
+    for directive in filter.directives.iter() {
+        let _ = write!(
+            spec,
+            ",{}={}",
+            directive.target,
+            directive.level.as_str().to_ascii_lowercase()
+        );
+    }
+    spec
+}
+
+/// Replaces the filter with the specification.
+///
+/// If the specification does not set the default level, the default level is kept.
+pub fn set_spec(spec: &str) -> Result<(), InvalidSpecError> {
+    let mut default = None;
+    let mut directives = Vec::new();
+    for directive in spec.split(',').map(str::trim) {
+        if directive.is_empty() {
+            continue;
+        }
+        match directive.split_once('=') {
+            Some((target, level)) => {
+                let target = target.trim();
+                if target.is_empty() {
+                    return Err(InvalidSpecError);
+                }
+                directives.push(Directive {
+                    target: target.to_string(),
+                    level: parse_level(level)?,
+                });
+            }
+            None => default = Some(parse_level(directive)?),
+        }
+    }
+
+    let mut filter = FILTER.lock();
+    if let Some(default) = default {
+        filter.default = default;
+    }
+    filter.directives = directives;
+    log::set_max_level(filter.max_level());
+    Ok(())
+}
+
+fn parse_level(level: &str) -> Result<LevelFilter, InvalidSpecError> {
+    LevelFilter::from_str(level.trim()).map_err(|_| InvalidSpecError)
+}
+
+#[cfg(ktest)]
+mod test {
+    use ostd::prelude::*;
+
+    use super::*;
+
+    #[ktest]
+    fn longest_target_wins() {
+        let filter = Filter {
+            default: LevelFilter::Warn,
+            directives: alloc::vec![
+                Directive {
+                    target: "aster_nix::fs".to_string(),
+                    level: LevelFilter::Debug,
+                },
+                Directive {
+                    target: "aster_nix::fs::ext2".to_string(),
+                    level: LevelFilter::Off,
+                },
+            ],
+        };
+        assert_eq!(filter.level_of("aster_nix::fs::ramfs"), LevelFilter::Debug);
+        assert_eq!(
+            filter.level_of("aster_nix::fs::ext2::inode"),
+            LevelFilter::Off
+        );
+        assert_eq!(filter.level_of("aster_nix::fsx"), LevelFilter::Warn);
+        assert_eq!(filter.max_level(), LevelFilter::Debug);
+    }
+}
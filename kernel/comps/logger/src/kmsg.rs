@@ -0,0 +1,231 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! The kernel message buffer.
+//!
+//! The log messages are kept as structured entries in a ring buffer, where the new entries
+//! overwrite the oldest ones. Each entry has a sequence number, a timestamp, a syslog priority
+//! and a subsystem tag, so that the entries can be read by the log collectors in userspace
+//! via `/dev/kmsg` and `syslog(2)`.
+//!
+//! The buffer does not allocate memory, so that it can be written in any context. The texts
+//! that are too long are truncated.
+
+use core::{fmt, time::Duration};
+
+use log::Level;
+use ostd::{
+    sync::{LocalIrqDisabled, SpinLock},
+    timer::Jiffies,
+};
+
+/// The maximum number of entries in the buffer.
+pub const KMSG_CAPACITY: usize = 512;
+
+/// The maximum length of the text of an entry.
+pub const MAX_TEXT_LEN: usize = 256;
+
+/// The maximum length of the subsystem tag of an entry.
+pub const MAX_SUBSYSTEM_LEN: usize = 48;
+
+/// The syslog facility of the kernel messages.
+pub const LOG_KERN: u8 = 0;
+/// The syslog facility of the messages written by the user programs.
+pub const LOG_USER: u8 = 1 << 3;
+
+static KMSG: SpinLock<Kmsg, LocalIrqDisabled> = SpinLock::new(Kmsg::new());
+
+struct Kmsg {
+    entries: [KmsgEntry; KMSG_CAPACITY],
+    /// The sequence number of the next entry.
+    next_seq: u64,
+    /// The sequence number of the first entry that is not cleared.
+    clear_seq: u64,
+}
+
+impl Kmsg {
+    const fn new() -> Self {
+        Self {
+            entries: [const { KmsgEntry::EMPTY }; KMSG_CAPACITY],
+            next_seq: 0,
+            clear_seq: 0,
+        }
+    }
+
+    fn first_seq(&self) -> u64 {
+        self.next_seq.saturating_sub(KMSG_CAPACITY as u64)
+    }
+
+    fn push(&mut self, mut entry: KmsgEntry) {
+        entry.seq = self.next_seq;
+        self.entries[(self.next_seq % KMSG_CAPACITY as u64) as usize] = entry;
+        self.next_seq += 1;
+    }
+}
+
+/// An entry in the kernel message buffer.
+#[derive(Clone)]
+pub struct KmsgEntry {
+    seq: u64,
+    timestamp: Duration,
+    prio: u8,
+    subsystem: FixedStr<MAX_SUBSYSTEM_LEN>,
+    text: FixedStr<MAX_TEXT_LEN>,
+}
+
+impl KmsgEntry {
+    const EMPTY: Self = Self {
+        seq: 0,
+        timestamp: Duration::ZERO,
+        prio: 0,
+        subsystem: FixedStr::new(),
+        text: FixedStr::new(),
+    };
+
+    /// Returns the sequence number.
+    pub fn seq(&self) -> u64 {
+        self.seq
+    }
+
+    /// Returns the time since the system boots up when the entry is added.
+    pub fn timestamp(&self) -> Duration {
+        self.timestamp
+    }
+
+    /// Returns the syslog priority, which consists of the facility and the level.
+    pub fn prio(&self) -> u8 {
+        self.prio
+    }
+
+    /// Returns the syslog level.
+    pub fn level(&self) -> u8 {
+        self.prio & 0x7
+    }
+
+    /// Returns the subsystem tag, which is empty for the messages from the user programs.
+    pub fn subsystem(&self) -> &str {
+        self.subsystem.as_str()
+    }
+
+    /// Returns the text.
+    pub fn text(&self) -> &str {
+        self.text.as_str()
+    }
+}
+
+/// A string with a fixed capacity, which truncates the text that does not fit.
+#[derive(Clone)]
+struct FixedStr<const N: usize> {
+    buf: [u8; N],
+    len: usize,
+}
+
+impl<const N: usize> FixedStr<N> {
+    const fn new() -> Self {
+        Self {
+            buf: [0; N],
+            len: 0,
+        }
+    }
+
+    fn as_str(&self) -> &str {
+        // The buffer only contains the complete characters written by `write_str`.
+        core::str::from_utf8(&self.buf[..self.len]).unwrap()
+    }
+}
+
+impl<const N: usize> fmt::Write for FixedStr<N> {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        let mut len = s.len().min(N - self.len);
+        while !s.is_char_boundary(len) {
+            len -= 1;
+        }
+        self.buf[self.len..self.len + len].copy_from_slice(&s.as_bytes()[..len]);
+        self.len += len;
+        Ok(())
+    }
+}
+
+/// Returns the syslog level of the log level.
+pub fn syslog_level(level: Level) -> u8 {
+    match level {
+        Level::Error => 3,
+        Level::Warn => 4,
+        Level::Info => 6,
+        Level::Debug | Level::Trace => 7,
+    }
+}
+
+/// Adds an entry to the buffer.
+pub fn append(prio: u8, subsystem: &str, text: fmt::Arguments) {
+    let mut entry = KmsgEntry::EMPTY;
+    entry.timestamp = Jiffies::elapsed().as_duration();
+    entry.prio = prio;
+    let _ = fmt::Write::write_str(&mut entry.subsystem, subsystem);
+    // Format the text without holding the lock, since formatting may log messages.
+    let _ = fmt::Write::write_fmt(&mut entry.text, text);
+    entry.text.len = entry.text.as_str().trim_end_matches('\n').len();
+
+    KMSG.lock().push(entry);
+}
+
+/// Reads the entry with the sequence number.
+///
+/// It returns `None` if the entry has been overwritten or has not been added yet.
+pub fn read(seq: u64) -> Option<KmsgEntry> {
+    let kmsg = KMSG.lock();
+    if seq < kmsg.first_seq() || seq >= kmsg.next_seq {
+        return None;
+    }
+    Some(kmsg.entries[(seq % KMSG_CAPACITY as u64) as usize].clone())
+}
+
+/// Returns the sequence number of the oldest entry in the buffer.
+pub fn first_seq() -> u64 {
+    KMSG.lock().first_seq()
+}
+
+/// Returns the sequence number of the next entry to be added.
+pub fn next_seq() -> u64 {
+    KMSG.lock().next_seq
+}
+
+/// Returns the sequence number of the oldest entry that is not cleared.
+pub fn clear_seq() -> u64 {
+    let kmsg = KMSG.lock();
+    kmsg.clear_seq.max(kmsg.first_seq())
+}
+
+/// Clears the buffer for the readers that read from [`clear_seq`].
+///
+/// The entries are not removed, so the readers that read from [`first_seq`] can still read
+/// them.
+pub fn clear() {
+    let mut kmsg = KMSG.lock();
+    kmsg.clear_seq = kmsg.next_seq;
+}
+
+#[cfg(ktest)]
+mod test {
+    use ostd::prelude::*;
+
+    use super::*;
+
+    #[ktest]
+    fn fixed_str_truncates() {
+        let mut s = FixedStr::<4>::new();
+        fmt::Write::write_str(&mut s, "ab\u{e9}c").unwrap();
+        assert_eq!(s.as_str(), "ab\u{e9}");
+        fmt::Write::write_str(&mut s, "d").unwrap();
+        assert_eq!(s.as_str(), "ab\u{e9}");
+    }
+
+    #[ktest]
+    fn kmsg_overwrites_oldest() {
+        let mut kmsg = Kmsg::new();
+        for _ in 0..KMSG_CAPACITY + 2 {
+            kmsg.push(KmsgEntry::EMPTY);
+        }
+        assert_eq!(kmsg.first_seq(), 2);
+        assert_eq!(kmsg.entries[0].seq(), KMSG_CAPACITY as u64);
+    }
+}
@@ -2,9 +2,11 @@
 
 //! The logger implementation for Asterinas.
 //!
-//! This logger now has the most basic logging functionality, controls the output
-//! based on the globally set log level. Different log levels will be represented
-//! with different colors if enabling `log_color` feature.
+//! The log messages are filtered by their levels and targets (the subsystem tags) with a
+//! filter that can be changed at runtime. The messages that pass the filter are kept in the
+//! kernel message buffer, and the ones that are important enough according to the console
+//! log level are printed to the console. Different log levels will be represented with
+//! different colors if enabling `log_color` feature.
 //!
 //! This logger guarantees _atomicity_ under concurrency: messages are always
 //! printed in their entirety without being mixed with messages generated
@@ -20,7 +22,13 @@ use component::{init_component, ComponentInitError};
 
 mod aster_logger;
 mod console;
+pub mod filter;
+pub mod kmsg;
 
+pub use aster_logger::{
+    console_loglevel, log_user_message, set_console_loglevel, MAX_CONSOLE_LOGLEVEL,
+    MIN_CONSOLE_LOGLEVEL,
+};
 pub use console::_print;
 
 #[init_component]
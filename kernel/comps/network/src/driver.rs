@@ -1,21 +1,22 @@
 // SPDX-License-Identifier: MPL-2.0
 
-use alloc::vec;
+use alloc::{vec, vec::Vec};
 
 use aster_bigtcp::{
-    device::{self, NotifyDevice},
+    device::{self, FilterMulticast, NotifyDevice},
     time::Instant,
+    wire::EthernetAddress,
 };
 use ostd::mm::VmWriter;
 
-use crate::{buffer::RxBuffer, AnyNetworkDevice};
+use crate::{buffer::RxBuffer, napi, AnyNetworkDevice, EthernetAddr};
 
 impl device::Device for dyn AnyNetworkDevice {
     type RxToken<'a> = RxToken;
     type TxToken<'a> = TxToken<'a>;
 
     fn receive(&mut self, _timestamp: Instant) -> Option<(Self::RxToken<'_>, Self::TxToken<'_>)> {
-        if self.can_receive() && self.can_send() {
+        if self.can_receive() && self.can_send() && napi::consume_rx_quota() {
             let rx_buffer = self.receive().unwrap();
             Some((RxToken(rx_buffer), TxToken(self)))
         } else {
@@ -42,6 +43,16 @@ impl NotifyDevice for dyn AnyNetworkDevice {
     }
 }
 
+impl FilterMulticast for dyn AnyNetworkDevice {
+    fn set_multicast_filter(&mut self, ether_addrs: &[EthernetAddress]) {
+        let ether_addrs: Vec<EthernetAddr> = ether_addrs
+            .iter()
+            .map(|ether_addr| EthernetAddr(ether_addr.0))
+            .collect();
+        self.set_multicast_filter(&ether_addrs);
+    }
+}
+
 pub struct RxToken(RxBuffer);
 
 impl device::RxToken for RxToken {
@@ -9,17 +9,25 @@
 mod buffer;
 pub mod dma_pool;
 mod driver;
+mod napi;
 
 extern crate alloc;
 
 use alloc::{collections::BTreeMap, string::String, sync::Arc, vec::Vec};
-use core::{any::Any, fmt::Debug};
+use core::{
+    any::Any,
+    fmt::Debug,
+    sync::atomic::{AtomicU64, Ordering},
+};
 
 use aster_bigtcp::device::DeviceCapabilities;
 pub use buffer::{RxBuffer, TxBuffer, RX_BUFFER_POOL, TX_BUFFER_LEN};
 use component::{init_component, ComponentInitError};
 pub use dma_pool::DmaSegment;
+use napi::Napi;
+pub use napi::NAPI_BUDGET;
 use ostd::{
+    cpu::local::PerCpu,
     sync::{LocalIrqDisabled, SpinLock},
     Pod,
 };
@@ -64,6 +72,19 @@ pub trait AnyNetworkDevice: Send + Sync + Any + Debug {
     /// for the entire duration of the polling process.
     /// Thus two polling process cannot happen simultaneously.
     fn notify_poll_end(&mut self);
+
+    /// Disables the interrupts that are raised when packets are received.
+    fn disable_recv_irq(&mut self);
+
+    /// Enables the interrupts that are raised when packets are received.
+    fn enable_recv_irq(&mut self);
+
+    /// Sets the multicast addresses whose frames should be received.
+    ///
+    /// The device may still receive the frames sent to other multicast addresses (e.g., if it
+    /// cannot filter that many addresses). The frames sent to the broadcast address and the MAC
+    /// address of the device are always received.
+    fn set_multicast_filter(&mut self, ether_addrs: &[EthernetAddr]);
 }
 
 pub trait NetDeviceIrqHandler = Fn() + Send + Sync + 'static;
@@ -88,8 +109,11 @@ pub fn get_device(str: &str) -> Option<Arc<SpinLock<dyn AnyNetworkDevice, LocalI
 
 /// Registers callback which will be called when receiving message.
 ///
-/// Since the callback will be called in interrupt context,
+/// Since the callback will be called in softirq context,
 /// the callback function should NOT sleep.
+///
+/// The callback is called repeatedly until the device has no more packets to receive, and the
+/// device will yield at most [`NAPI_BUDGET`] packets to each call.
 pub fn register_recv_callback(name: &str, callback: impl NetDeviceIrqHandler) {
     let device_table = COMPONENT.get().unwrap().network_device_table.lock();
     let Some(callbacks) = device_table.get(name) else {
@@ -106,16 +130,22 @@ pub fn register_send_callback(name: &str, callback: impl NetDeviceIrqHandler) {
     callbacks.send_callbacks.lock().push(Arc::new(callback));
 }
 
+/// Handles the interrupt raised when the device receives packets.
+///
+/// Instead of calling the receive callbacks in the interrupt context, this function disables
+/// the receive interrupts and lets the callbacks poll the device in the softirq context.
 pub fn handle_recv_irq(name: &str) {
     let device_table = COMPONENT.get().unwrap().network_device_table.lock();
     let Some(callbacks) = device_table.get(name) else {
         return;
     };
 
-    let callbacks = callbacks.recv_callbacks.lock();
-    for callback in callbacks.iter() {
-        callback();
-    }
+    callbacks
+        .irq_stats
+        .get()
+        .nr_recv_irqs
+        .fetch_add(1, Ordering::Relaxed);
+    callbacks.napi.schedule();
 }
 
 pub fn handle_send_irq(name: &str) {
@@ -123,6 +153,11 @@ pub fn handle_send_irq(name: &str) {
     let Some(callbacks) = device_table.get(name) else {
         return;
     };
+    callbacks
+        .irq_stats
+        .get()
+        .nr_send_irqs
+        .fetch_add(1, Ordering::Relaxed);
 
     let can_send = {
         let mut device = callbacks.device.lock();
@@ -139,6 +174,35 @@ pub fn handle_send_irq(name: &str) {
     }
 }
 
+/// The interrupt statistics of a network device.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct NetDeviceIrqStats {
+    /// The number of the interrupts raised when the device receives packets.
+    pub nr_recv_irqs: u64,
+    /// The number of the interrupts raised when the device sends packets.
+    pub nr_send_irqs: u64,
+}
+
+/// Returns the interrupt statistics of the device, which are summed up over all the CPUs.
+pub fn irq_stats(name: &str) -> Option<NetDeviceIrqStats> {
+    let device_table = COMPONENT.get().unwrap().network_device_table.lock();
+    let callbacks = device_table.get(name)?;
+
+    let stats =
+        callbacks
+            .irq_stats
+            .iter()
+            .fold(NetDeviceIrqStats::default(), |stats, (_, cpu_stats)| {
+                NetDeviceIrqStats {
+                    nr_recv_irqs: stats.nr_recv_irqs
+                        + cpu_stats.nr_recv_irqs.load(Ordering::Relaxed),
+                    nr_send_irqs: stats.nr_send_irqs
+                        + cpu_stats.nr_send_irqs.load(Ordering::Relaxed),
+                }
+            });
+    Some(stats)
+}
+
 pub fn all_devices() -> Vec<(String, NetworkDeviceRef)> {
     let network_devs = COMPONENT.get().unwrap().network_device_table.lock();
     network_devs
@@ -158,6 +222,7 @@ fn init() -> Result<(), ComponentInitError> {
     COMPONENT.call_once(|| a);
     NETWORK_IRQ_HANDLERS.call_once(|| SpinLock::new(Vec::new()));
     buffer::init();
+    napi::init();
     Ok(())
 }
 
@@ -175,14 +240,26 @@ struct NetworkDeviceIrqCallbackSet {
     device: NetworkDeviceRef,
     recv_callbacks: NetDeviceIrqHandlerListRef,
     send_callbacks: NetDeviceIrqHandlerListRef,
+    napi: Arc<Napi>,
+    /// The interrupt counts on each CPU, which are updated without contention.
+    irq_stats: PerCpu<CpuIrqStats>,
+}
+
+#[derive(Default)]
+struct CpuIrqStats {
+    nr_recv_irqs: AtomicU64,
+    nr_send_irqs: AtomicU64,
 }
 
 impl NetworkDeviceIrqCallbackSet {
     fn new(device: NetworkDeviceRef) -> Self {
+        let recv_callbacks: NetDeviceIrqHandlerListRef = Arc::new(SpinLock::new(Vec::new()));
         Self {
+            napi: Napi::new(device.clone(), recv_callbacks.clone()),
             device,
-            recv_callbacks: Arc::new(SpinLock::new(Vec::new())),
+            recv_callbacks,
             send_callbacks: Arc::new(SpinLock::new(Vec::new())),
+            irq_stats: PerCpu::new(|_| CpuIrqStats::default()),
         }
     }
 }
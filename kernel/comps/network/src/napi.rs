@@ -0,0 +1,151 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! NAPI-style polling of the received packets.
+//!
+//! Under load, a network device may raise an interrupt for every packet that it receives.
+//! To reduce the interrupt rate, the receive interrupt handler of a device only disables
+//! further receive interrupts and schedules the [`Napi`] instance of the device. The
+//! scheduled instances are polled in the softirq context, where the receive callbacks drain
+//! the packets in batches of at most [`NAPI_BUDGET`] packets. The receive interrupts are
+//! enabled again only after the device has no more packets to receive.
+
+use alloc::sync::Arc;
+use core::{
+    cell::RefCell,
+    ops::DerefMut,
+    sync::atomic::{AtomicBool, Ordering},
+};
+
+use aster_softirq::{softirq_id::NET_RX_SOFTIRQ_ID, SoftIrqLine};
+use intrusive_collections::{intrusive_adapter, LinkedList, LinkedListAtomicLink};
+use ostd::{cpu_local, cpu_local_cell, trap};
+
+use crate::{NetDeviceIrqHandlerListRef, NetworkDeviceRef};
+
+/// The maximum number of packets that are received in one round of polling a device.
+pub const NAPI_BUDGET: usize = 64;
+
+/// The NAPI context of a network device.
+pub(crate) struct Napi {
+    device: NetworkDeviceRef,
+    recv_callbacks: NetDeviceIrqHandlerListRef,
+    /// Whether the instance is scheduled, in which case the receive interrupts are disabled.
+    is_scheduled: AtomicBool,
+    link: LinkedListAtomicLink,
+}
+
+intrusive_adapter!(NapiAdapter = Arc<Napi>: Napi { link: LinkedListAtomicLink });
+
+cpu_local! {
+    static POLL_LIST: RefCell<LinkedList<NapiAdapter>> = RefCell::new(LinkedList::new(NapiAdapter::NEW));
+}
+
+cpu_local_cell! {
+    /// The number of packets that can still be received in the current round of polling.
+    ///
+    /// The value is `usize::MAX` if the current CPU is not polling any devices, in which
+    /// case the number of packets is not limited.
+    static RX_QUOTA: usize = usize::MAX;
+}
+
+impl Napi {
+    pub(crate) fn new(
+        device: NetworkDeviceRef,
+        recv_callbacks: NetDeviceIrqHandlerListRef,
+    ) -> Arc<Self> {
+        Arc::new(Self {
+            device,
+            recv_callbacks,
+            is_scheduled: AtomicBool::new(false),
+            link: LinkedListAtomicLink::new(),
+        })
+    }
+
+    /// Disables the receive interrupts and schedules the instance to be polled.
+    ///
+    /// If the instance has been scheduled, this method does nothing.
+    pub(crate) fn schedule(self: &Arc<Self>) {
+        if self
+            .is_scheduled
+            .compare_exchange(false, true, Ordering::Acquire, Ordering::Relaxed)
+            .is_err()
+        {
+            return;
+        }
+        self.device.lock().disable_recv_irq();
+
+        let irq_guard = trap::disable_local();
+        POLL_LIST
+            .get_with(&irq_guard)
+            .borrow_mut()
+            .push_back(self.clone());
+        SoftIrqLine::get(NET_RX_SOFTIRQ_ID).raise();
+    }
+
+    /// Polls the device once, returning whether the device should be polled again.
+    fn poll(self: &Arc<Self>) -> bool {
+        RX_QUOTA.store(NAPI_BUDGET);
+        for callback in self.recv_callbacks.lock().iter() {
+            callback();
+        }
+        let is_budget_exhausted = RX_QUOTA.load() == 0;
+        RX_QUOTA.store(usize::MAX);
+
+        let mut device = self.device.lock();
+        if is_budget_exhausted && device.can_receive() {
+            return true;
+        }
+
+        // Clear the flag with the device locked, so that a concurrent `schedule` cannot
+        // disable the receive interrupts before they are enabled below.
+        self.is_scheduled.store(false, Ordering::Release);
+        device.enable_recv_irq();
+        // The packets that are received before the interrupts are enabled raise no interrupts.
+        let has_missed_packets = device.can_receive();
+        drop(device);
+
+        if has_missed_packets {
+            self.schedule();
+        }
+        false
+    }
+}
+
+/// Consumes the quota of one received packet, returning `false` if the quota is exhausted.
+pub(crate) fn consume_rx_quota() -> bool {
+    match RX_QUOTA.load() {
+        0 => false,
+        usize::MAX => true,
+        quota => {
+            RX_QUOTA.store(quota - 1);
+            true
+        }
+    }
+}
+
+pub(crate) fn init() {
+    SoftIrqLine::get(NET_RX_SOFTIRQ_ID).enable(net_rx_softirq_handler);
+}
+
+/// Polls the scheduled devices on the current CPU.
+///
+/// A device that has more packets to receive after it is polled is put back to the list,
+/// and will be polled again when the softirq is processed next time.
+fn net_rx_softirq_handler() {
+    let mut processing_list = {
+        let irq_guard = trap::disable_local();
+        let guard = POLL_LIST.get_with(&irq_guard);
+        let mut list_mut = guard.borrow_mut();
+        LinkedList::take(list_mut.deref_mut())
+    };
+
+    while let Some(napi) = processing_list.pop_front() {
+        if !napi.poll() {
+            continue;
+        }
+
+        let irq_guard = trap::disable_local();
+        POLL_LIST.get_with(&irq_guard).borrow_mut().push_back(napi);
+        SoftIrqLine::get(NET_RX_SOFTIRQ_ID).raise();
+    }
+}
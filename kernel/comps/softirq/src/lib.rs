@@ -7,10 +7,14 @@
 extern crate alloc;
 
 use alloc::boxed::Box;
-use core::sync::atomic::{AtomicU8, Ordering};
+use core::sync::atomic::{AtomicU8, AtomicUsize, Ordering};
 
 use component::{init_component, ComponentInitError};
-use ostd::{cpu_local_cell, trap::register_bottom_half_handler};
+use ostd::{
+    cpu::{local::PerCpu, CpuId},
+    cpu_local_cell,
+    trap::register_bottom_half_handler,
+};
 use spin::Once;
 
 pub mod softirq_id;
@@ -99,6 +103,12 @@ impl SoftIrqLine {
     pub fn is_enabled(&self) -> bool {
         ENABLED_MASK.load(Ordering::Acquire) & (1 << self.id) != 0
     }
+
+    /// Returns the number of times that the callback of this softirq line has been
+    /// executed on the CPU.
+    pub fn nr_processed_on(&self, cpu: CpuId) -> usize {
+        NR_PROCESSED.get_on_cpu(cpu)[self.id as usize].load(Ordering::Relaxed)
+    }
 }
 
 /// A slice that stores the [`SoftIrqLine`]s, whose ID is equal to its offset in the slice.
@@ -109,6 +119,7 @@ fn init() -> Result<(), ComponentInitError> {
     let lines: [SoftIrqLine; SoftIrqLine::NR_LINES as usize] =
         core::array::from_fn(|i| SoftIrqLine::new(i as u8));
     LINES.call_once(|| lines);
+    NR_PROCESSED.init(|_| core::array::from_fn(|_| AtomicUsize::new(0)));
     register_bottom_half_handler(process_pending);
 
     taskless::init();
@@ -121,6 +132,9 @@ cpu_local_cell! {
     static PENDING_MASK: u8 = 0;
 }
 
+/// The number of times that the callback of each softirq line has been executed on each CPU.
+static NR_PROCESSED: PerCpu<[AtomicUsize; SoftIrqLine::NR_LINES as usize]> = PerCpu::uninit();
+
 /// Processes pending softirqs.
 ///
 /// The processing instructions will iterate for `SOFTIRQ_RUN_TIMES` times. If any softirq
@@ -138,9 +152,11 @@ fn process_pending() {
         if action_mask == 0 {
             break;
         }
+        let nr_processed = NR_PROCESSED.get();
         while action_mask > 0 {
             let action_id = u8::trailing_zeros(action_mask) as u8;
             SoftIrqLine::get(action_id).callback.get().unwrap()();
+            nr_processed[action_id as usize].fetch_add(1, Ordering::Relaxed);
             action_mask &= action_mask - 1;
         }
     }
@@ -11,3 +11,7 @@ pub const TIMER_SOFTIRQ_ID: u8 = 1;
 
 /// The corresponding softirq line is used to schedule general taskless jobs.
 pub const TASKLESS_SOFTIRQ_ID: u8 = 2;
+
+/// The corresponding softirq line is used to poll the network devices that have
+/// received packets.
+pub const NET_RX_SOFTIRQ_ID: u8 = 3;
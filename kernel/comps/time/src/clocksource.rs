@@ -149,6 +149,17 @@ impl ClockSource {
         self.base.freq
     }
 
+    /// Calculates the instant when the counter has the cycles.
+    ///
+    /// Returns `None` if the cycles are earlier than the last record, e.g., the cycles are read
+    /// before the `ClockSource` is updated.
+    pub fn instant_at(&self, instant_cycles: u64) -> Option<Instant> {
+        let (last_instant, last_cycles) = self.last_record();
+        let delta_cycles = instant_cycles.checked_sub(last_cycles)?;
+        let delta_nanos = self.cycles_to_nanos_lossy(delta_cycles);
+        Some(last_instant + Duration::from_nanos(delta_nanos))
+    }
+
     /// Calibrates the recorded `Instant` to zero, and record the instant cycles.
     pub(crate) fn calibrate(&self, instant_cycles: u64) {
         self.update_last_record((Instant::zero(), instant_cycles));
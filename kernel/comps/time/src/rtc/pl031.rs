@@ -0,0 +1,39 @@
+// SPDX-License-Identifier: MPL-2.0
+
+use chrono::{DateTime, Datelike, Timelike};
+use ostd::{arch::aarch64::timer::PL031_IO_MEM, mm::VmIoOnce};
+
+use crate::{rtc::Driver, SystemTime};
+
+pub struct RtcPl031;
+
+impl Driver for RtcPl031 {
+    fn try_new() -> Option<RtcPl031> {
+        PL031_IO_MEM.get()?;
+        Some(RtcPl031)
+    }
+
+    fn read_rtc(&self) -> SystemTime {
+        /// The data register, which counts the seconds since the Unix epoch.
+        const RTCDR: usize = 0;
+
+        let io_mem = PL031_IO_MEM.get().unwrap();
+        let timestamp: u32 = io_mem.read_once(RTCDR).unwrap();
+
+        let time = DateTime::from_timestamp(timestamp as i64, 0)
+            .unwrap()
+            .naive_utc();
+        let (is_ad, year) = time.year_ce();
+        debug_assert!(is_ad, "non-negative timestamp should always be AD");
+
+        SystemTime {
+            year: year as u16,
+            month: time.month() as u8,
+            day: time.day() as u8,
+            hour: time.hour() as u8,
+            minute: time.minute() as u8,
+            second: time.second() as u8,
+            nanos: 0,
+        }
+    }
+}
@@ -0,0 +1,209 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! The per-CPU ring buffers of the recorded events.
+
+use alloc::{collections::VecDeque, string::String, vec::Vec};
+use core::{
+    fmt::{self, Write},
+    mem::size_of,
+    sync::atomic::{AtomicUsize, Ordering},
+};
+
+use ostd::{
+    arch::{read_tsc, tsc_freq},
+    cpu::{all_cpus, CpuId, PinCurrentCpu},
+    cpu_local,
+    sync::{LocalIrqDisabled, Mutex, SpinLock},
+    trap,
+};
+
+use crate::{Tracepoint, MAX_ARGS};
+
+/// The default size of the ring buffer of each CPU in KiB.
+const DEFAULT_BUFFER_SIZE_KB: usize = 64;
+
+/// The size of the ring buffer of each CPU in KiB.
+static BUFFER_SIZE_KB: AtomicUsize = AtomicUsize::new(DEFAULT_BUFFER_SIZE_KB);
+
+/// Serializes the resizing of the ring buffers.
+static RESIZE_LOCK: Mutex<()> = Mutex::new(());
+
+cpu_local! {
+    static BUFFER: SpinLock<RingBuffer, LocalIrqDisabled> = SpinLock::new(RingBuffer::new());
+}
+
+/// A recorded event.
+#[derive(Clone, Copy)]
+struct TraceRecord {
+    /// The TSC value when the event is recorded.
+    timestamp: u64,
+    cpu: CpuId,
+    tracepoint: &'static Tracepoint,
+    args: [u64; MAX_ARGS],
+}
+
+/// A ring buffer that keeps the most recent records.
+struct RingBuffer {
+    records: VecDeque<TraceRecord>,
+    /// The maximum number of records.
+    ///
+    /// The records are preallocated, so that recording an event never allocates memory.
+    capacity: usize,
+    /// The number of records written, including the overwritten ones.
+    num_written: usize,
+}
+
+impl RingBuffer {
+    const fn new() -> Self {
+        Self {
+            records: VecDeque::new(),
+            capacity: 0,
+            num_written: 0,
+        }
+    }
+
+    fn push(&mut self, record: TraceRecord) {
+        if self.capacity == 0 {
+            return;
+        }
+        if self.records.len() == self.capacity {
+            self.records.pop_front();
+        }
+        self.records.push_back(record);
+        self.num_written += 1;
+    }
+}
+
+pub(crate) fn init() {
+    resize(DEFAULT_BUFFER_SIZE_KB);
+}
+
+pub(crate) fn record(tracepoint: &'static Tracepoint, args: [u64; MAX_ARGS]) {
+    let irq_guard = trap::disable_local();
+    let cpu = irq_guard.current_cpu();
+    let record = TraceRecord {
+        timestamp: read_tsc(),
+        cpu,
+        tracepoint,
+        args,
+    };
+    BUFFER.get_with(&irq_guard).lock().push(record);
+}
+
+/// Returns the size of the ring buffer of each CPU in KiB.
+pub fn buffer_size_kb() -> usize {
+    BUFFER_SIZE_KB.load(Ordering::Relaxed)
+}
+
+/// Resizes the ring buffer of each CPU, keeping the most recent events.
+///
+/// # Panics
+///
+/// This function panics if it is called in atomic mode.
+pub fn set_buffer_size_kb(size_kb: usize) {
+    let _guard = RESIZE_LOCK.lock();
+    resize(size_kb);
+    BUFFER_SIZE_KB.store(size_kb, Ordering::Relaxed);
+}
+
+fn resize(size_kb: usize) {
+    let capacity = size_kb * 1024 / size_of::<TraceRecord>();
+
+    for cpu in all_cpus() {
+        // Allocate the new records before disabling IRQs.
+        let mut records = VecDeque::with_capacity(capacity);
+
+        let mut buffer = BUFFER.get_on_cpu(cpu).lock();
+        let num_kept = buffer.records.len().min(capacity);
+        let num_dropped = buffer.records.len() - num_kept;
+        records.extend(buffer.records.drain(num_dropped..));
+        buffer.records = records;
+        buffer.capacity = capacity;
+    }
+}
+
+/// Discards the recorded events.
+pub fn clear() {
+    for cpu in all_cpus() {
+        let mut buffer = BUFFER.get_on_cpu(cpu).lock();
+        buffer.records.clear();
+        buffer.num_written = 0;
+    }
+}
+
+/// Formats the recorded events of all CPUs in the order of their timestamps.
+///
+/// The format follows the `trace` file of ftrace in Linux.
+pub fn read_trace() -> String {
+    let mut records = Vec::new();
+    let mut num_written = 0;
+    for cpu in all_cpus() {
+        let buffer = BUFFER.get_on_cpu(cpu).lock();
+        records.extend(buffer.records.iter().copied());
+        num_written += buffer.num_written;
+    }
+    records.sort_by_key(|record| record.timestamp);
+
+    let mut output = String::new();
+    writeln!(output, "# tracer: nop").unwrap();
+    writeln!(output, "#").unwrap();
+    writeln!(
+        output,
+        "# entries-in-buffer/entries-written: {}/{}   #P:{}",
+        records.len(),
+        num_written,
+        all_cpus().count()
+    )
+    .unwrap();
+    writeln!(output, "#").unwrap();
+    writeln!(output, "#    CPU     TIMESTAMP  EVENT").unwrap();
+    writeln!(output, "#     |         |         |").unwrap();
+    for record in records.iter() {
+        writeln!(output, "{}", record).unwrap();
+    }
+    output
+}
+
+impl fmt::Display for TraceRecord {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let nanos = (self.timestamp as u128 * 1_000_000_000 / tsc_freq().max(1) as u128) as u64;
+        write!(
+            f,
+            "    [{:03}] {:6}.{:06}: {}:{}: ",
+            self.cpu.as_usize(),
+            nanos / 1_000_000_000,
+            nanos % 1_000_000_000 / 1000,
+            self.tracepoint.subsystem(),
+            self.tracepoint.name()
+        )?;
+        self.tracepoint.fmt_args(&self.args, f)
+    }
+}
+
+#[cfg(ktest)]
+mod test {
+    use ostd::prelude::*;
+
+    use super::*;
+    use crate::events::PAGE_FAULT;
+
+    #[ktest]
+    fn overwrite_oldest_records() {
+        let mut buffer = RingBuffer::new();
+        buffer.records = VecDeque::with_capacity(2);
+        buffer.capacity = 2;
+
+        for i in 0..3 {
+            buffer.push(TraceRecord {
+                timestamp: i,
+                cpu: CpuId::bsp(),
+                tracepoint: &PAGE_FAULT,
+                args: [i; MAX_ARGS],
+            });
+        }
+
+        assert_eq!(buffer.num_written, 3);
+        let timestamps: Vec<u64> = buffer.records.iter().map(|r| r.timestamp).collect();
+        assert_eq!(timestamps, [1, 2]);
+    }
+}
@@ -0,0 +1,97 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! The tracepoints in the kernel.
+//!
+//! The TID in the arguments of an event is zero for the kernel threads.
+
+use core::fmt;
+
+use crate::{Tracepoint, MAX_ARGS};
+
+/// The entry of a system call.
+///
+/// Arguments: the TID, the system call number, and the first two arguments of the call.
+pub static SYS_ENTER: Tracepoint = Tracepoint::new("syscalls", "sys_enter", fmt_sys_enter);
+
+/// The exit of a system call.
+///
+/// Arguments: the TID, the system call number, and the return value.
+pub static SYS_EXIT: Tracepoint = Tracepoint::new("syscalls", "sys_exit", fmt_sys_exit);
+
+/// A switch from a thread to another on a CPU.
+///
+/// Arguments: the TIDs of the previous thread and the next thread.
+pub static SCHED_SWITCH: Tracepoint = Tracepoint::new("sched", "sched_switch", fmt_sched_switch);
+
+/// A page fault that is handled by the virtual memory subsystem.
+///
+/// Arguments: the TID, the faulting address, and the required permissions.
+pub static PAGE_FAULT: Tracepoint = Tracepoint::new("mm", "page_fault", fmt_page_fault);
+
+/// A request that is submitted to a virtqueue.
+///
+/// Arguments: the queue index, the token, and the number of descriptors.
+pub static VIRTQUEUE_SUBMIT: Tracepoint =
+    Tracepoint::new("virtio", "virtqueue_submit", fmt_virtqueue_submit);
+
+/// A request that is completed by the device of a virtqueue.
+///
+/// Arguments: the queue index, the token, and the number of bytes written by the device.
+pub static VIRTQUEUE_COMPLETE: Tracepoint =
+    Tracepoint::new("virtio", "virtqueue_complete", fmt_virtqueue_complete);
+
+/// A hit of a kprobe at the entry of a kernel function.
+///
+/// Arguments: the address of the function, and its first three arguments.
+pub static KPROBE: Tracepoint = Tracepoint::new("kprobes", "kprobe", fmt_kprobe);
+
+/// All the tracepoints.
+pub static ALL_TRACEPOINTS: [&Tracepoint; 7] = [
+    &SYS_ENTER,
+    &SYS_EXIT,
+    &SCHED_SWITCH,
+    &PAGE_FAULT,
+    &VIRTQUEUE_SUBMIT,
+    &VIRTQUEUE_COMPLETE,
+    &KPROBE,
+];
+
+fn fmt_sys_enter(args: &[u64; MAX_ARGS], f: &mut fmt::Formatter) -> fmt::Result {
+    write!(
+        f,
+        "tid={} nr={} args=({:#x}, {:#x}, ...)",
+        args[0], args[1], args[2], args[3]
+    )
+}
+
+fn fmt_sys_exit(args: &[u64; MAX_ARGS], f: &mut fmt::Formatter) -> fmt::Result {
+    write!(f, "tid={} nr={} ret={}", args[0], args[1], args[2] as i64)
+}
+
+fn fmt_sched_switch(args: &[u64; MAX_ARGS], f: &mut fmt::Formatter) -> fmt::Result {
+    write!(f, "prev_tid={} ==> next_tid={}", args[0], args[1])
+}
+
+fn fmt_page_fault(args: &[u64; MAX_ARGS], f: &mut fmt::Formatter) -> fmt::Result {
+    write!(
+        f,
+        "tid={} addr={:#x} perms={:#x}",
+        args[0], args[1], args[2]
+    )
+}
+
+fn fmt_virtqueue_submit(args: &[u64; MAX_ARGS], f: &mut fmt::Formatter) -> fmt::Result {
+    write!(f, "queue={} token={} descs={}", args[0], args[1], args[2])
+}
+
+fn fmt_virtqueue_complete(args: &[u64; MAX_ARGS], f: &mut fmt::Formatter) -> fmt::Result {
+    write!(f, "queue={} token={} len={}", args[0], args[1], args[2])
+}
+
+fn fmt_kprobe(args: &[u64; MAX_ARGS], f: &mut fmt::Formatter) -> fmt::Result {
+    write!(
+        f,
+        "addr={:#x} args=({:#x}, {:#x}, {:#x}, ...)",
+        args[0], args[1], args[2], args[3]
+    )
+}
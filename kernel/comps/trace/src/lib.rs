@@ -0,0 +1,80 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! The kernel tracing framework.
+//!
+//! The kernel is instrumented with statically defined [`Tracepoint`]s, which are listed in
+//! [`events`]. When tracing is on and a tracepoint is enabled, each event at the tracepoint
+//! is recorded into the ring buffer of the current CPU. A ring buffer keeps the most recent
+//! events, overwriting the oldest ones when it is full. The recorded events of all CPUs can
+//! be read as text with [`read_trace`], similar to the `trace` file of ftrace in Linux.
+//!
+//! A disabled tracepoint costs only an atomic load, so tracepoints can be placed in hot
+//! paths. The arguments of an event are recorded as raw integers, and are formatted only
+//! when the events are read.
+//!
+//! # Example
+//!
+//! ```
+//! use aster_trace::{events::PAGE_FAULT, trace_event};
+//!
+//! PAGE_FAULT.set_enabled(true);
+//! trace_event!(PAGE_FAULT, tid, fault_addr, required_perms);
+//! ```
+#![no_std]
+#![deny(unsafe_code)]
+
+extern crate alloc;
+
+use core::sync::atomic::{AtomicBool, Ordering};
+
+use component::{init_component, ComponentInitError};
+
+mod buffer;
+pub mod events;
+mod tracepoint;
+
+pub use buffer::{buffer_size_kb, clear, read_trace, set_buffer_size_kb};
+pub use tracepoint::{Tracepoint, MAX_ARGS};
+
+static IS_TRACING_ON: AtomicBool = AtomicBool::new(true);
+
+/// Returns whether the events at the enabled tracepoints are recorded.
+pub fn is_tracing_on() -> bool {
+    IS_TRACING_ON.load(Ordering::Relaxed)
+}
+
+/// Turns tracing on or off.
+///
+/// Turning tracing off stops recording events without disabling the tracepoints, so the
+/// recorded events can be examined without being overwritten.
+pub fn set_tracing_on(is_on: bool) {
+    IS_TRACING_ON.store(is_on, Ordering::Relaxed);
+}
+
+/// Records an event at a tracepoint if the tracepoint is enabled.
+///
+/// The arguments are converted to `u64` with `as`, and are not evaluated if the tracepoint
+/// is disabled. At most [`MAX_ARGS`] arguments can be given.
+#[macro_export]
+macro_rules! trace_event {
+    ($tracepoint:expr $(, $arg:expr)* $(,)?) => {
+        if $tracepoint.should_record() {
+            $tracepoint.record($crate::__pad_args([$($arg as u64),*]));
+        }
+    };
+}
+
+#[doc(hidden)]
+pub fn __pad_args<const N: usize>(args: [u64; N]) -> [u64; MAX_ARGS] {
+    const { assert!(N <= MAX_ARGS) };
+
+    let mut padded = [0; MAX_ARGS];
+    padded[..N].copy_from_slice(&args);
+    padded
+}
+
+#[init_component]
+fn init() -> Result<(), ComponentInitError> {
+    buffer::init();
+    Ok(())
+}
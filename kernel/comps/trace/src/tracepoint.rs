@@ -0,0 +1,86 @@
+// SPDX-License-Identifier: MPL-2.0
+
+use core::{
+    fmt,
+    sync::atomic::{AtomicBool, Ordering},
+};
+
+use crate::buffer;
+
+/// The maximum number of arguments of an event.
+pub const MAX_ARGS: usize = 4;
+
+/// The function that formats the arguments of an event.
+type FmtArgsFn = fn(&[u64; MAX_ARGS], &mut fmt::Formatter) -> fmt::Result;
+
+/// A statically defined point in the kernel where events can be recorded.
+///
+/// Tracepoints are disabled by default.
+pub struct Tracepoint {
+    subsystem: &'static str,
+    name: &'static str,
+    fmt_args: FmtArgsFn,
+    is_enabled: AtomicBool,
+}
+
+impl Tracepoint {
+    /// Creates a disabled tracepoint.
+    pub const fn new(subsystem: &'static str, name: &'static str, fmt_args: FmtArgsFn) -> Self {
+        Self {
+            subsystem,
+            name,
+            fmt_args,
+            is_enabled: AtomicBool::new(false),
+        }
+    }
+
+    /// Returns the subsystem that the tracepoint belongs to, e.g., `"syscalls"`.
+    pub fn subsystem(&self) -> &'static str {
+        self.subsystem
+    }
+
+    /// Returns the name of the tracepoint, e.g., `"sys_enter"`.
+    pub fn name(&self) -> &'static str {
+        self.name
+    }
+
+    /// Returns whether the tracepoint is enabled.
+    pub fn is_enabled(&self) -> bool {
+        self.is_enabled.load(Ordering::Relaxed)
+    }
+
+    /// Enables or disables the tracepoint.
+    pub fn set_enabled(&self, is_enabled: bool) {
+        self.is_enabled.store(is_enabled, Ordering::Relaxed);
+    }
+
+    /// Returns whether the events at the tracepoint should be recorded now.
+    #[inline]
+    pub fn should_record(&self) -> bool {
+        self.is_enabled() && crate::is_tracing_on()
+    }
+
+    /// Records an event at the tracepoint.
+    ///
+    /// This method can be called in any context, including the interrupt context. Use
+    /// [`trace_event!`] instead, which skips recording if the tracepoint is disabled.
+    ///
+    /// [`trace_event!`]: crate::trace_event
+    pub fn record(&'static self, args: [u64; MAX_ARGS]) {
+        buffer::record(self, args);
+    }
+
+    pub(crate) fn fmt_args(&self, args: &[u64; MAX_ARGS], f: &mut fmt::Formatter) -> fmt::Result {
+        (self.fmt_args)(args, f)
+    }
+}
+
+impl fmt::Debug for Tracepoint {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Tracepoint")
+            .field("subsystem", &self.subsystem)
+            .field("name", &self.name)
+            .field("is_enabled", &self.is_enabled)
+            .finish_non_exhaustive()
+    }
+}
@@ -0,0 +1,77 @@
+// SPDX-License-Identifier: MPL-2.0
+
+use alloc::{boxed::Box, sync::Arc};
+use core::hint::spin_loop;
+
+use log::debug;
+use ostd::{
+    mm::{DmaDirection, DmaStream, DmaStreamSlice, FrameAllocOptions, VmIo, PAGE_SIZE},
+    sync::SpinLock,
+};
+
+use crate::{device::VirtioDeviceError, queue::VirtQueue, transport::VirtioTransport};
+
+/// A VirtIO entropy device (i.e., virtio-rng), which provides random bytes from the host.
+#[derive(Debug)]
+pub struct EntropyDevice {
+    queue: SpinLock<VirtQueue>,
+    transport: SpinLock<Box<dyn VirtioTransport>>,
+    buffer: DmaStream,
+}
+
+impl EntropyDevice {
+    const QUEUE_SIZE: u16 = 1;
+
+    /// Creates a new VirtIO-Entropy driver and registers it.
+    pub(crate) fn init(mut transport: Box<dyn VirtioTransport>) -> Result<(), VirtioDeviceError> {
+        let queue = VirtQueue::new(0, Self::QUEUE_SIZE, transport.as_mut())
+            .expect("create virtqueue failed");
+        let buffer = {
+            let segment = FrameAllocOptions::new().alloc_segment(1).unwrap();
+            DmaStream::map(segment.into(), DmaDirection::FromDevice, false).unwrap()
+        };
+        transport.finish_init();
+
+        let device = Arc::new(Self {
+            queue: SpinLock::new(queue),
+            transport: SpinLock::new(transport),
+            buffer,
+        });
+        debug!("[Virtio]: An entropy device is found");
+        super::register_device(device);
+        Ok(())
+    }
+
+    /// Negotiate features for the device specified bits 0~23
+    pub(crate) fn negotiate_features(_features: u64) -> u64 {
+        // The device has no features.
+        0
+    }
+
+    /// Reads the random bytes from the device into `buf`, returning the number of bytes read.
+    ///
+    /// The device may return fewer bytes than requested. This function is blocking. It busy
+    /// waits for the completion of the request.
+    pub fn read(&self, buf: &mut [u8]) -> usize {
+        let len = buf.len().min(PAGE_SIZE);
+        if len == 0 {
+            return 0;
+        }
+        let slice = DmaStreamSlice::new(&self.buffer, 0, len);
+
+        let mut queue = self.queue.disable_irq().lock();
+        let token = queue.add_dma_buf(&[], &[&slice]).expect("add queue failed");
+        if queue.should_notify() {
+            queue.notify();
+        }
+        while !queue.can_pop() {
+            spin_loop();
+        }
+        let used_len = queue.pop_used_with_token(token).expect("pop used failed") as usize;
+
+        let used_len = used_len.min(len);
+        slice.sync().unwrap();
+        slice.read_bytes(0, &mut buf[..used_len]).unwrap();
+        used_len
+    }
+}
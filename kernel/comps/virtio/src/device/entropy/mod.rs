@@ -0,0 +1,23 @@
+// SPDX-License-Identifier: MPL-2.0
+
+pub mod device;
+
+use alloc::{sync::Arc, vec::Vec};
+
+use ostd::sync::SpinLock;
+
+use self::device::EntropyDevice;
+
+pub static DEVICE_NAME: &str = "Virtio-Entropy";
+
+static DEVICES: SpinLock<Vec<Arc<EntropyDevice>>> = SpinLock::new(Vec::new());
+
+pub fn register_device(device: Arc<EntropyDevice>) {
+    DEVICES.lock().push(device);
+}
+
+/// Returns all the entropy devices, which are the hardware sources of the kernel random number
+/// generator.
+pub fn all_devices() -> Vec<Arc<EntropyDevice>> {
+    DEVICES.lock().clone()
+}
@@ -9,7 +9,7 @@ use ostd::task::scheduler::info;
 use ostd::{
     sync::SpinLock,
     mm::{DmaDirection, DmaStream, DmaStreamSlice, FrameAllocOptions, HasPaddr, VmIo},
-    trap::TrapFrame,
+    trap::{IrqThread, TrapFrame},
 };
 use crate::device::gpu::GPU_DEVICE;
 
@@ -112,13 +112,18 @@ impl GPUDevice {
         });
 
         // Handle interrupt (ref. block device)
+        // The completions are processed in an IRQ thread, so that they are not processed
+        // with interrupts disabled.
         let cloned_device = device.clone();
-        let handle_irq = move |_: &TrapFrame| {
+        let irq_thread = Arc::new(IrqThread::new(move || {
             cloned_device.handle_irq();
+        }));
+        let cloned_irq_thread = irq_thread.clone();
+        let handle_irq = move |_: &TrapFrame| {
+            cloned_irq_thread.wake();
         };
-        let clone_device = device.clone();
         let handle_irq_cursor = move |_: &TrapFrame| {
-            clone_device.handle_irq();
+            irq_thread.wake();
         };
         let cloned_device = device.clone();
         let handle_config_change = move |_: &TrapFrame| {
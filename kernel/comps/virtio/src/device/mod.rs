@@ -6,10 +6,12 @@ use crate::queue::QueueError;
 
 pub mod block;
 pub mod console;
+pub mod entropy;
+pub mod gpu;
 pub mod input;
 pub mod network;
+pub mod pmem;
 pub mod socket;
-pub mod gpu;
 #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, TryFromInt)]
 #[repr(u8)]
 pub enum VirtioDeviceType {
@@ -36,6 +38,7 @@ pub enum VirtioDeviceType {
     Pstore = 22,
     IOMMU = 23,
     Memory = 24,
+    Pmem = 27,
 }
 
 #[derive(Debug)]
@@ -47,6 +50,8 @@ pub enum VirtioDeviceError {
     QueueUnknownError,
     /// The input virtio capability list contains invalid element
     CapabilityListError,
+    /// The device configuration is not supported
+    UnsupportedConfig,
 }
 
 impl From<QueueError> for VirtioDeviceError {
@@ -0,0 +1,55 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! The commands sent through the control queue.
+//!
+//! See "5.1.6.5 Control Virtqueue" in the virtio specification.
+
+use ostd::Pod;
+
+/// The header that precedes each command.
+#[repr(C)]
+#[derive(Default, Debug, Clone, Copy, Pod)]
+pub struct VirtioNetCtrlHdr {
+    pub class: u8,
+    pub command: u8,
+}
+
+impl VirtioNetCtrlHdr {
+    pub const fn new(class: CtrlClass, command: u8) -> Self {
+        Self {
+            class: class as u8,
+            command,
+        }
+    }
+}
+
+#[repr(u8)]
+#[derive(Debug, Clone, Copy)]
+pub enum CtrlClass {
+    /// Controls the receive filtering (requires `VIRTIO_NET_F_CTRL_RX`).
+    Rx = 0,
+    /// Controls the MAC address filtering (requires `VIRTIO_NET_F_CTRL_RX`).
+    Mac = 1,
+}
+
+/// The commands in [`CtrlClass::Rx`].
+///
+/// Each command is followed by a byte, which turns the mode on if it is nonzero.
+pub const VIRTIO_NET_CTRL_RX_PROMISC: u8 = 0;
+pub const VIRTIO_NET_CTRL_RX_ALLMULTI: u8 = 1;
+
+/// The commands in [`CtrlClass::Mac`].
+///
+/// [`VIRTIO_NET_CTRL_MAC_TABLE_SET`] is followed by two tables, the unicast table and the
+/// multicast table. Each table consists of a little-endian 32-bit entry count and the MAC
+/// addresses.
+pub const VIRTIO_NET_CTRL_MAC_TABLE_SET: u8 = 0;
+
+/// The acknowledgement that the device writes back if the command succeeds.
+pub const VIRTIO_NET_OK: u8 = 0;
+
+/// The maximum number of the multicast MAC addresses that we put in the filter.
+///
+/// The device may not be able to filter too many addresses, in which case we receive all the
+/// multicast frames instead. This follows the size of the MAC table in QEMU.
+pub const MAX_MULTICAST_FILTER_LEN: usize = 64;
@@ -3,7 +3,7 @@
 use alloc::{
     boxed::Box, collections::linked_list::LinkedList, string::ToString, sync::Arc, vec::Vec,
 };
-use core::{fmt::Debug, mem::size_of};
+use core::{fmt::Debug, hint::spin_loop, mem::size_of};
 
 use aster_bigtcp::device::{Checksum, DeviceCapabilities, Medium};
 use aster_network::{
@@ -12,12 +12,19 @@ use aster_network::{
 use aster_util::slot_vec::SlotVec;
 use log::{debug, warn};
 use ostd::{
-    mm::DmaStream,
+    mm::{DmaDirection, DmaStream, DmaStreamSlice, FrameAllocOptions, VmIo, PAGE_SIZE},
     sync::{LocalIrqDisabled, SpinLock},
     trap::TrapFrame,
 };
 
-use super::{config::VirtioNetConfig, header::VirtioNetHdr};
+use super::{
+    config::VirtioNetConfig,
+    control::{
+        CtrlClass, VirtioNetCtrlHdr, MAX_MULTICAST_FILTER_LEN, VIRTIO_NET_CTRL_MAC_TABLE_SET,
+        VIRTIO_NET_CTRL_RX_ALLMULTI, VIRTIO_NET_CTRL_RX_PROMISC, VIRTIO_NET_OK,
+    },
+    header::VirtioNetHdr,
+};
 use crate::{
     device::{network::config::NetworkFeatures, VirtioDeviceError},
     queue::{QueueError, VirtQueue},
@@ -31,6 +38,8 @@ pub struct NetworkDevice {
     mac_addr: EthernetAddr,
     send_queue: VirtQueue,
     recv_queue: VirtQueue,
+    /// The control queue, which is available if the device supports receive filtering.
+    ctrl_queue: Option<CtrlQueue>,
     // Since the virtio net header remains consistent for each sending packet,
     // we store it to avoid recreating the header repeatedly.
     header: VirtioNetHdr,
@@ -55,6 +64,76 @@ impl PollStatistics {
     }
 }
 
+/// The control queue along with the buffer to hold the commands.
+struct CtrlQueue {
+    queue: VirtQueue,
+    buffer: DmaStream,
+}
+
+impl CtrlQueue {
+    fn new(transport: &mut dyn VirtioTransport) -> Result<Self, VirtioDeviceError> {
+        let mut queue = VirtQueue::new(QUEUE_CTRL, QUEUE_SIZE, transport)?;
+        // The commands are sent synchronously, so no interrupts are needed.
+        queue.disable_callback();
+
+        let buffer = {
+            let segment = FrameAllocOptions::new().alloc_segment(1).unwrap();
+            DmaStream::map(segment.into(), DmaDirection::Bidirectional, false).unwrap()
+        };
+
+        Ok(Self { queue, buffer })
+    }
+
+    /// Sends a command and waits for the device to acknowledge it.
+    fn send_command(&mut self, hdr: VirtioNetCtrlHdr, data: &[u8]) -> Result<(), VirtioNetError> {
+        // The last byte of the buffer is reserved for the acknowledgement.
+        let req_len = size_of::<VirtioNetCtrlHdr>() + data.len();
+        if req_len >= PAGE_SIZE {
+            return Err(VirtioNetError::Busy);
+        }
+
+        let req_slice = DmaStreamSlice::new(&self.buffer, 0, req_len);
+        req_slice.write_val(0, &hdr).unwrap();
+        req_slice
+            .write_bytes(size_of::<VirtioNetCtrlHdr>(), data)
+            .unwrap();
+        req_slice.sync().unwrap();
+
+        let ack_slice = DmaStreamSlice::new(&self.buffer, PAGE_SIZE - 1, 1);
+        ack_slice.write_val(0, &u8::MAX).unwrap();
+        ack_slice.sync().unwrap();
+
+        let token = self
+            .queue
+            .add_dma_buf(&[&req_slice], &[&ack_slice])
+            .map_err(queue_to_network_error)?;
+        if self.queue.should_notify() {
+            self.queue.notify();
+        }
+        while !self.queue.can_pop() {
+            spin_loop();
+        }
+        self.queue
+            .pop_used_with_token(token)
+            .map_err(queue_to_network_error)?;
+
+        ack_slice.sync().unwrap();
+        let ack: u8 = ack_slice.read_val(0).unwrap();
+        if ack != VIRTIO_NET_OK {
+            return Err(VirtioNetError::Unknown);
+        }
+
+        Ok(())
+    }
+
+    fn set_rx_mode(&mut self, command: u8, is_on: bool) -> Result<(), VirtioNetError> {
+        self.send_command(
+            VirtioNetCtrlHdr::new(CtrlClass::Rx, command),
+            &[is_on as u8],
+        )
+    }
+}
+
 impl NetworkDevice {
     pub(crate) fn negotiate_features(device_features: u64) -> u64 {
         let device_features = NetworkFeatures::from_bits_truncate(device_features);
@@ -91,6 +170,12 @@ impl NetworkDevice {
         let mut recv_queue = VirtQueue::new(QUEUE_RECV, QUEUE_SIZE, transport.as_mut())
             .expect("creating recv queue fails");
 
+        let mut ctrl_queue = if features.contains(NetworkFeatures::VIRTIO_NET_F_CTRL_RX) {
+            Some(CtrlQueue::new(transport.as_mut())?)
+        } else {
+            None
+        };
+
         let tx_buffers = (0..QUEUE_SIZE).map(|_| None).collect();
 
         let mut rx_buffers = SlotVec::new();
@@ -107,12 +192,15 @@ impl NetworkDevice {
             recv_queue.notify();
         }
 
+        device_init_rx_mode(ctrl_queue.as_mut());
+
         let mut device = Self {
             config_manager,
             caps,
             mac_addr,
             send_queue,
             recv_queue,
+            ctrl_queue,
             header: VirtioNetHdr::default(),
             tx_buffers,
             rx_buffers,
@@ -266,6 +354,22 @@ impl NetworkDevice {
     }
 }
 
+/// Disables the promiscuous mode, so that the device filters the frames by the MAC addresses.
+///
+/// Until the multicast filter is set, all the multicast frames are received.
+fn device_init_rx_mode(ctrl_queue: Option<&mut CtrlQueue>) {
+    let Some(ctrl_queue) = ctrl_queue else {
+        return;
+    };
+
+    let result = ctrl_queue
+        .set_rx_mode(VIRTIO_NET_CTRL_RX_ALLMULTI, true)
+        .and_then(|_| ctrl_queue.set_rx_mode(VIRTIO_NET_CTRL_RX_PROMISC, false));
+    if let Err(err) = result {
+        warn!("failed to initialize the receive mode: {:?}", err);
+    }
+}
+
 fn queue_to_network_error(err: QueueError) -> VirtioNetError {
     match err {
         QueueError::NotReady => VirtioNetError::NotReady,
@@ -349,6 +453,44 @@ impl AnyNetworkDevice for NetworkDevice {
         self.notify_send_queue();
         self.notify_receive_queue();
     }
+
+    fn disable_recv_irq(&mut self) {
+        self.recv_queue.disable_callback();
+    }
+
+    fn enable_recv_irq(&mut self) {
+        self.recv_queue.enable_callback();
+    }
+
+    fn set_multicast_filter(&mut self, ether_addrs: &[EthernetAddr]) {
+        let Some(ctrl_queue) = self.ctrl_queue.as_mut() else {
+            return;
+        };
+
+        // If there are too many addresses, just receive all the multicast frames.
+        let is_overflowed = ether_addrs.len() > MAX_MULTICAST_FILTER_LEN;
+
+        if !is_overflowed {
+            let mut tables = Vec::with_capacity(size_of::<u32>() * 2 + ether_addrs.len() * 6);
+            // The unicast table is empty, since the frames sent to our own MAC address are
+            // always received.
+            tables.extend_from_slice(&0u32.to_le_bytes());
+            tables.extend_from_slice(&(ether_addrs.len() as u32).to_le_bytes());
+            for ether_addr in ether_addrs {
+                tables.extend_from_slice(&ether_addr.0);
+            }
+
+            let hdr = VirtioNetCtrlHdr::new(CtrlClass::Mac, VIRTIO_NET_CTRL_MAC_TABLE_SET);
+            if let Err(err) = ctrl_queue.send_command(hdr, &tables) {
+                warn!("failed to set the multicast filter: {:?}", err);
+                return;
+            }
+        }
+
+        if let Err(err) = ctrl_queue.set_rx_mode(VIRTIO_NET_CTRL_RX_ALLMULTI, is_overflowed) {
+            warn!("failed to set the all-multicast mode: {:?}", err);
+        }
+    }
 }
 
 impl Debug for NetworkDevice {
@@ -368,5 +510,6 @@ static TX_BUFFER_POOL: SpinLock<LinkedList<DmaStream>, LocalIrqDisabled> =
 
 const QUEUE_RECV: u16 = 0;
 const QUEUE_SEND: u16 = 1;
+const QUEUE_CTRL: u16 = 2;
 
 const QUEUE_SIZE: u16 = 64;
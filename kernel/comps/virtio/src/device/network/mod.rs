@@ -1,6 +1,7 @@
 // SPDX-License-Identifier: MPL-2.0
 
 pub mod config;
+pub mod control;
 pub mod device;
 pub mod header;
 
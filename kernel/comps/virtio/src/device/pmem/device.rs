@@ -0,0 +1,219 @@
+// SPDX-License-Identifier: MPL-2.0
+
+use alloc::{boxed::Box, format, sync::Arc};
+use core::{
+    hint::spin_loop,
+    mem::size_of,
+    sync::atomic::{AtomicUsize, Ordering},
+};
+
+use aster_block::{
+    bio::{BioEnqueueError, BioStatus, BioType, SubmittedBio},
+    id::Bid,
+    BlockDeviceMeta, BLOCK_SIZE, SECTOR_SIZE,
+};
+use log::{debug, warn};
+use ostd::{
+    impl_untyped_frame_meta_for,
+    mm::{
+        max_paddr, DmaDirection, DmaStream, DmaStreamSlice, FrameAllocOptions, Segment, UFrame,
+        UntypedMem, VmIo, PAGE_SIZE,
+    },
+    sync::SpinLock,
+    Pod,
+};
+
+use super::{ReqType, VirtioPmemConfig};
+use crate::{device::VirtioDeviceError, queue::VirtQueue, transport::VirtioTransport};
+
+/// The metadata of the frames in a persistent memory region.
+///
+/// The frames are owned by the device for its whole lifetime,
+/// so they are never returned to the frame allocator.
+#[derive(Debug)]
+pub struct PmemFrameMeta;
+
+impl_untyped_frame_meta_for!(PmemFrameMeta);
+
+/// A VirtIO persistent memory device.
+///
+/// The memory region of the device is exposed as a block device, whose reads and
+/// writes are plain memory copies. File systems on the device can also map the
+/// frames of the region into user space directly with [`direct_access`].
+///
+/// [`direct_access`]: aster_block::BlockDevice::direct_access
+#[derive(Debug)]
+pub struct PmemDevice {
+    region: Segment<PmemFrameMeta>,
+    queue: SpinLock<VirtQueue>,
+    transport: SpinLock<Box<dyn VirtioTransport>>,
+    request: DmaStream,
+    response: DmaStream,
+}
+
+impl PmemDevice {
+    const QUEUE_SIZE: u16 = 2;
+
+    /// Creates a new VirtIO-PMEM driver and registers it as a block device.
+    pub(crate) fn init(mut transport: Box<dyn VirtioTransport>) -> Result<(), VirtioDeviceError> {
+        let config = VirtioPmemConfig::new_manager(transport.as_ref()).read_config();
+        debug!("virtio_pmem_config = {:?}", config);
+
+        let start = config.start as usize;
+        let size = config.size as usize;
+        if size == 0 || start % PAGE_SIZE != 0 || size % PAGE_SIZE != 0 {
+            return Err(VirtioDeviceError::UnsupportedConfig);
+        }
+        // The frames in the region must have metadata so that they can be mapped into
+        // user space. The region is otherwise unreachable for now.
+        if start
+            .checked_add(size)
+            .map_or(true, |end| end > max_paddr())
+        {
+            warn!(
+                "[Virtio]: The PMEM region {:#x}..{:#x} is out of the managed physical memory",
+                start,
+                start + size
+            );
+            return Err(VirtioDeviceError::UnsupportedConfig);
+        }
+        let region = Segment::from_unused(start..start + size, |_| PmemFrameMeta);
+
+        let queue = VirtQueue::new(0, Self::QUEUE_SIZE, transport.as_mut())
+            .expect("create virtqueue failed");
+        let request = {
+            let segment = FrameAllocOptions::new().alloc_segment(1).unwrap();
+            DmaStream::map(segment.into(), DmaDirection::ToDevice, false).unwrap()
+        };
+        let response = {
+            let segment = FrameAllocOptions::new().alloc_segment(1).unwrap();
+            DmaStream::map(segment.into(), DmaDirection::FromDevice, false).unwrap()
+        };
+        transport.finish_init();
+
+        let device = Arc::new(Self {
+            region,
+            queue: SpinLock::new(queue),
+            transport: SpinLock::new(transport),
+            request,
+            response,
+        });
+
+        static NEXT_ID: AtomicUsize = AtomicUsize::new(0);
+        let device_id = format!("pmem{}", NEXT_ID.fetch_add(1, Ordering::Relaxed));
+        aster_block::register_device(device_id, device);
+        Ok(())
+    }
+
+    /// Negotiate features for the device specified bits 0~23
+    pub(crate) fn negotiate_features(_features: u64) -> u64 {
+        // `VIRTIO_PMEM_F_SHMEM_REGION` is not supported.
+        0
+    }
+
+    /// Copies the data of a read or write bio between the region and its segments.
+    fn copy(&self, bio: &SubmittedBio) -> BioStatus {
+        let mut offset = bio.sid_range().start.to_raw() as usize * SECTOR_SIZE;
+        for segment in bio.segments() {
+            if offset + segment.nbytes() > self.region.size() {
+                return BioStatus::IoError;
+            }
+            let done = match bio.type_() {
+                BioType::Read => {
+                    let mut reader = self.region.reader().skip(offset).limit(segment.nbytes());
+                    segment.writer().unwrap().write(&mut reader)
+                }
+                _ => {
+                    let mut writer = self.region.writer().skip(offset).limit(segment.nbytes());
+                    writer.write(&mut segment.reader().unwrap())
+                }
+            };
+            offset += done;
+        }
+        BioStatus::Complete
+    }
+
+    /// Asks the host to persist the writes to the region.
+    ///
+    /// This function is blocking. It busy waits for the completion of the request.
+    fn flush(&self) -> BioStatus {
+        let req_slice = DmaStreamSlice::new(&self.request, 0, REQ_SIZE);
+        let resp_slice = DmaStreamSlice::new(&self.response, 0, RESP_SIZE);
+
+        let mut queue = self.queue.disable_irq().lock();
+        let req = PmemReq {
+            type_: ReqType::Flush as _,
+        };
+        req_slice.write_val(0, &req).unwrap();
+        req_slice.sync().unwrap();
+        resp_slice.write_val(0, &PmemResp::default()).unwrap();
+
+        let token = queue
+            .add_dma_buf(&[&req_slice], &[&resp_slice])
+            .expect("add queue failed");
+        if queue.should_notify() {
+            queue.notify();
+        }
+        while !queue.can_pop() {
+            spin_loop();
+        }
+        queue.pop_used_with_token(token).expect("pop used failed");
+
+        resp_slice.sync().unwrap();
+        let resp: PmemResp = resp_slice.read_val(0).unwrap();
+        if resp.ret == 0 {
+            BioStatus::Complete
+        } else {
+            BioStatus::IoError
+        }
+    }
+}
+
+impl aster_block::BlockDevice for PmemDevice {
+    fn enqueue(&self, bio: SubmittedBio) -> Result<(), BioEnqueueError> {
+        let status = match bio.type_() {
+            BioType::Read | BioType::Write => self.copy(&bio),
+            BioType::Flush => self.flush(),
+            BioType::Discard => BioStatus::NotSupported,
+        };
+        bio.complete(status);
+        Ok(())
+    }
+
+    fn metadata(&self) -> BlockDeviceMeta {
+        BlockDeviceMeta {
+            max_nr_segments_per_bio: usize::MAX,
+            nr_sectors: self.region.size() / SECTOR_SIZE,
+        }
+    }
+
+    fn direct_access(&self, bid: Bid) -> Option<UFrame> {
+        let offset = bid.to_offset();
+        if offset + BLOCK_SIZE > self.region.size() {
+            return None;
+        }
+        self.region
+            .slice(&(offset..offset + BLOCK_SIZE))
+            .next()
+            .map(UFrame::from)
+    }
+}
+
+/// VirtIO-PMEM request.
+#[repr(C)]
+#[derive(Debug, Copy, Clone, Pod)]
+struct PmemReq {
+    pub type_: u32,
+}
+
+const REQ_SIZE: usize = size_of::<PmemReq>();
+
+/// Response of a VirtIO-PMEM request.
+#[repr(C)]
+#[derive(Debug, Copy, Clone, Pod, Default)]
+struct PmemResp {
+    /// Zero on success, non-zero on failure.
+    pub ret: u32,
+}
+
+const RESP_SIZE: usize = size_of::<PmemResp>();
@@ -0,0 +1,55 @@
+// SPDX-License-Identifier: MPL-2.0
+
+pub mod device;
+
+use core::mem::offset_of;
+
+use aster_util::safe_ptr::SafePtr;
+use int_to_c_enum::TryFromInt;
+use ostd::Pod;
+
+use crate::transport::{ConfigManager, VirtioTransport};
+
+pub static DEVICE_NAME: &str = "Virtio-PMEM";
+
+#[repr(u32)]
+#[derive(Debug, Copy, Clone, TryFromInt)]
+pub enum ReqType {
+    /// Persists the writes to the memory region on the host.
+    Flush = 0,
+}
+
+#[derive(Debug, Copy, Clone, Pod)]
+#[repr(C)]
+pub struct VirtioPmemConfig {
+    /// The guest physical address of the memory region.
+    pub start: u64,
+    /// The length of the memory region in bytes.
+    pub size: u64,
+}
+
+impl VirtioPmemConfig {
+    pub(self) fn new_manager(transport: &dyn VirtioTransport) -> ConfigManager<Self> {
+        let safe_ptr = transport
+            .device_config_mem()
+            .map(|mem| SafePtr::new(mem, 0));
+        let bar_space = transport.device_config_bar();
+
+        ConfigManager::new(safe_ptr, bar_space)
+    }
+}
+
+impl ConfigManager<VirtioPmemConfig> {
+    pub(super) fn read_config(&self) -> VirtioPmemConfig {
+        let read_u64 = |offset: usize| {
+            let low = self.read_once::<u32>(offset).unwrap() as u64;
+            let high = self.read_once::<u32>(offset + 4).unwrap() as u64;
+            high << 32 | low
+        };
+
+        VirtioPmemConfig {
+            start: read_u64(offset_of!(VirtioPmemConfig, start)),
+            size: read_u64(offset_of!(VirtioPmemConfig, size)),
+        }
+    }
+}
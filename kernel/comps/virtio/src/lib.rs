@@ -18,10 +18,12 @@ use component::{init_component, ComponentInitError};
 use device::{
     block::device::BlockDevice,
     console::device::ConsoleDevice,
+    entropy::device::EntropyDevice,
+    gpu::device::GPUDevice,
     input::device::InputDevice,
     network::device::NetworkDevice,
+    pmem::device::PmemDevice,
     socket::{self, device::SocketDevice},
-    gpu::device::GPUDevice,
     VirtioDeviceType,
 };
 use log::{error, warn};
@@ -69,8 +71,10 @@ fn virtio_component_init() -> Result<(), ComponentInitError> {
             VirtioDeviceType::Input => InputDevice::init(transport),
             VirtioDeviceType::Network => NetworkDevice::init(transport),
             VirtioDeviceType::Console => ConsoleDevice::init(transport),
+            VirtioDeviceType::Entropy => EntropyDevice::init(transport),
             VirtioDeviceType::Socket => SocketDevice::init(transport),
             VirtioDeviceType::GPU => GPUDevice::init(transport),
+            VirtioDeviceType::Pmem => PmemDevice::init(transport),
             _ => {
                 warn!("[Virtio]: Found unimplemented device:{:?}", device_type);
                 Ok(())
@@ -105,8 +109,10 @@ fn negotiate_features(transport: &mut Box<dyn VirtioTransport>) {
         VirtioDeviceType::Block => BlockDevice::negotiate_features(device_specified_features),
         VirtioDeviceType::Input => InputDevice::negotiate_features(device_specified_features),
         VirtioDeviceType::Console => ConsoleDevice::negotiate_features(device_specified_features),
+        VirtioDeviceType::Entropy => EntropyDevice::negotiate_features(device_specified_features),
         VirtioDeviceType::Socket => SocketDevice::negotiate_features(device_specified_features),
         VirtioDeviceType::GPU => GPUDevice::negotiate_features(device_specified_features),
+        VirtioDeviceType::Pmem => PmemDevice::negotiate_features(device_specified_features),
         _ => device_specified_features,
     };
     let mut support_feature = Feature::from_bits_truncate(features);
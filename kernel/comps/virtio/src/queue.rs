@@ -9,6 +9,10 @@ use core::{
 };
 
 use aster_rights::{Dup, TRightSet, TRights, Write};
+use aster_trace::{
+    events::{VIRTQUEUE_COMPLETE, VIRTQUEUE_SUBMIT},
+    trace_event,
+};
 use aster_util::{field_ptr, safe_ptr::SafePtr};
 use bitflags::bitflags;
 use log::debug;
@@ -250,6 +254,13 @@ impl VirtQueue {
             .unwrap();
 
         fence(Ordering::SeqCst);
+
+        trace_event!(
+            VIRTQUEUE_SUBMIT,
+            self.queue_idx,
+            head,
+            inputs.len() + outputs.len()
+        );
         Ok(head)
     }
 
@@ -318,6 +329,7 @@ impl VirtQueue {
         self.recycle_descriptors(index as u16);
         self.last_used_idx = self.last_used_idx.wrapping_add(1);
 
+        trace_event!(VIRTQUEUE_COMPLETE, self.queue_idx, index, len);
         Ok((index as u16, len))
     }
 
@@ -346,6 +358,7 @@ impl VirtQueue {
         self.recycle_descriptors(index as u16);
         self.last_used_idx = self.last_used_idx.wrapping_add(1);
 
+        trace_event!(VIRTQUEUE_COMPLETE, self.queue_idx, index, len);
         Ok(len)
     }
 
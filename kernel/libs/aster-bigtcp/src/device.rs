@@ -3,6 +3,7 @@
 pub use smoltcp::phy::{
     Checksum, ChecksumCapabilities, Device, DeviceCapabilities, Loopback, Medium, RxToken, TxToken,
 };
+use smoltcp::wire::EthernetAddress;
 
 /// A trait that allows to obtain a mutable reference of [`Device`].
 ///
@@ -24,3 +25,9 @@ pub trait NotifyDevice {
     /// Notifies the device driver that polling has ended.
     fn notify_poll_end(&mut self);
 }
+
+/// A trait for programming the multicast filters of device drivers.
+pub trait FilterMulticast {
+    /// Sets the multicast Ethernet addresses whose frames should be received.
+    fn set_multicast_filter(&mut self, ether_addrs: &[EthernetAddress]);
+}
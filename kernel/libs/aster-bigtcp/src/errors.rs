@@ -9,6 +9,35 @@ pub enum BindError {
     InUse,
 }
 
+/// An error describing the reason why configuring an iface failed.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum IfaceConfigError {
+    /// The address or the route already exists.
+    Exists,
+    /// There is no room for more addresses or routes.
+    Full,
+}
+
+/// An error describing the reason why sending a link-layer frame failed.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum SendFrameError {
+    /// The iface does not support sending link-layer frames.
+    NotSupported,
+    /// The frame is larger than the maximum transmission unit.
+    TooLarge,
+    /// The device is not ready to send frames.
+    BufferFull,
+}
+
+/// An error describing the reason why sending an IP packet failed.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum SendIpPacketError {
+    /// The packet is not a well-formed IPv4 or IPv6 packet.
+    Malformed,
+    /// There are too many packets waiting to be sent.
+    BufferFull,
+}
+
 pub mod tcp {
     pub use smoltcp::socket::tcp::{RecvError, SendError};
 
@@ -1,6 +1,9 @@
 // SPDX-License-Identifier: MPL-2.0
 
-use crate::{iface::ScheduleNextPoll, socket::SocketEventObserver};
+use crate::{
+    iface::{FilterIpPacket, ScheduleNextPoll, TapFrame, TapIpPacket},
+    socket::SocketEventObserver,
+};
 
 /// Extension to be implemented by users of this crate.
 ///
@@ -14,6 +17,15 @@ pub trait Ext {
     /// The type for ifaces to schedule the next poll.
     type ScheduleNextPoll: ScheduleNextPoll;
 
+    /// The type for Ethernet ifaces to tap the link-layer frames.
+    type TapFrame: TapFrame;
+
+    /// The type for ifaces to tap the IP packets destined to the local host.
+    type TapIpPacket: TapIpPacket;
+
+    /// The type for ifaces to filter the IP packets at the netfilter-style hooks.
+    type FilterIpPacket: FilterIpPacket;
+
     /// The type for TCP sockets to observe events.
     type TcpEventObserver: SocketEventObserver + Clone;
 
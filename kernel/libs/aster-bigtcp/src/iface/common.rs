@@ -1,7 +1,10 @@
 // SPDX-License-Identifier: MPL-2.0
 
 use alloc::{
-    collections::btree_map::{BTreeMap, Entry},
+    collections::{
+        btree_map::{BTreeMap, Entry},
+        vec_deque::VecDeque,
+    },
     string::String,
     sync::Arc,
     vec::Vec,
@@ -9,21 +12,28 @@ use alloc::{
 
 use ostd::sync::{LocalIrqDisabled, SpinLock, SpinLockGuard};
 use smoltcp::{
-    iface::{packet::Packet, Context},
+    iface::{packet::Packet, Context, Route},
     phy::Device,
-    wire::{IpAddress, IpEndpoint, Ipv4Address, Ipv4Packet},
+    wire::{
+        EthernetAddress, HardwareAddress, IpAddress, IpCidr, IpEndpoint, IpVersion, Ipv4Address,
+        Ipv4Cidr, Ipv6Address, Ipv6Cidr,
+    },
 };
 
 use super::{
-    poll::{FnHelper, PollContext},
+    dhcp::DhcpLease,
+    filter::{FilterHook, FilterIpPacket, FilterPacket, FilterVerdict},
+    multicast::MulticastGroups,
+    poll::{FnHelper, IpPacket, PollContext},
     port::BindPortConfig,
+    stats::{IfaceStats, SnmpCounter},
     time::get_network_timestamp,
     Iface,
 };
 use crate::{
-    errors::BindError,
+    errors::{BindError, IfaceConfigError, SendIpPacketError},
     ext::Ext,
-    socket::{TcpListenerBg, UdpSocketBg},
+    socket::{TcpListenerBg, TcpSocketInfo, UdpSocketBg, UdpSocketInfo},
     socket_table::SocketTable,
 };
 
@@ -32,14 +42,27 @@ pub struct IfaceCommon<E: Ext> {
     interface: SpinLock<smoltcp::iface::Interface, LocalIrqDisabled>,
     used_ports: SpinLock<BTreeMap<u16, usize>, LocalIrqDisabled>,
     sockets: SpinLock<SocketTable<E>, LocalIrqDisabled>,
+    /// The lease that has been applied to the iface, if the iface is configured by DHCP.
+    dhcp_lease: SpinLock<Option<DhcpLease>, LocalIrqDisabled>,
+    /// The IP packets that are sent by [`Self::send_ip_packet`] and have not been dispatched.
+    ip_packets: SpinLock<VecDeque<Vec<u8>>, LocalIrqDisabled>,
+    multicast_groups: SpinLock<MulticastGroups, LocalIrqDisabled>,
+    stats: IfaceStats,
     sched_poll: E::ScheduleNextPoll,
+    tap_ip_packet: E::TapIpPacket,
+    filter_ip_packet: E::FilterIpPacket,
 }
 
+/// The maximum number of IP packets waiting to be dispatched.
+const MAX_PENDING_IP_PACKETS: usize = 64;
+
 impl<E: Ext> IfaceCommon<E> {
     pub(super) fn new(
         name: String,
         interface: smoltcp::iface::Interface,
         sched_poll: E::ScheduleNextPoll,
+        tap_ip_packet: E::TapIpPacket,
+        filter_ip_packet: E::FilterIpPacket,
     ) -> Self {
         let sockets = SocketTable::new();
 
@@ -48,7 +71,13 @@ impl<E: Ext> IfaceCommon<E> {
             interface: SpinLock::new(interface),
             used_ports: SpinLock::new(BTreeMap::new()),
             sockets: SpinLock::new(sockets),
+            dhcp_lease: SpinLock::new(None),
+            ip_packets: SpinLock::new(VecDeque::new()),
+            multicast_groups: SpinLock::new(MulticastGroups::new()),
+            stats: IfaceStats::new(),
             sched_poll,
+            tap_ip_packet,
+            filter_ip_packet,
         }
     }
 
@@ -60,12 +89,211 @@ impl<E: Ext> IfaceCommon<E> {
         self.interface.lock().ipv4_addr()
     }
 
+    pub(super) fn ipv6_addr(&self) -> Option<Ipv6Address> {
+        self.interface.lock().ipv6_addr()
+    }
+
     pub(super) fn sched_poll(&self) -> &E::ScheduleNextPoll {
         &self.sched_poll
     }
+
+    pub(crate) fn stats(&self) -> &IfaceStats {
+        &self.stats
+    }
 }
 
-// Lock order: interface -> sockets
+impl<E: Ext> IfaceCommon<E> {
+    pub(super) fn hardware_addr(&self) -> Option<EthernetAddress> {
+        match self.interface.lock().hardware_addr() {
+            HardwareAddress::Ethernet(ether_addr) => Some(ether_addr),
+            _ => None,
+        }
+    }
+
+    pub(super) fn ipv4_cidrs(&self) -> Vec<Ipv4Cidr> {
+        self.interface
+            .lock()
+            .ip_addrs()
+            .iter()
+            .filter_map(|ip_cidr| match ip_cidr {
+                IpCidr::Ipv4(ipv4_cidr) => Some(*ipv4_cidr),
+                IpCidr::Ipv6(_) => None,
+            })
+            .collect()
+    }
+
+    pub(super) fn ipv6_cidrs(&self) -> Vec<Ipv6Cidr> {
+        self.interface
+            .lock()
+            .ip_addrs()
+            .iter()
+            .filter_map(|ip_cidr| match ip_cidr {
+                IpCidr::Ipv4(_) => None,
+                IpCidr::Ipv6(ipv6_cidr) => Some(*ipv6_cidr),
+            })
+            .collect()
+    }
+
+    pub(super) fn add_ip_cidr(&self, ip_cidr: IpCidr) -> Result<(), IfaceConfigError> {
+        let mut result = Ok(());
+
+        self.interface.lock().update_ip_addrs(|ip_addrs| {
+            if ip_addrs
+                .iter()
+                .any(|old_cidr| old_cidr.address() == ip_cidr.address())
+            {
+                result = Err(IfaceConfigError::Exists);
+            } else if ip_addrs.push(ip_cidr).is_err() {
+                result = Err(IfaceConfigError::Full);
+            }
+        });
+
+        // The device should start receiving the frames sent to the solicited-node multicast
+        // address of the new IPv6 address.
+        if result.is_ok() && matches!(ip_cidr, IpCidr::Ipv6(_)) {
+            self.multicast_groups.lock().mark_filter_dirty();
+        }
+
+        result
+    }
+
+    pub(super) fn dhcp_lease(&self) -> Option<DhcpLease> {
+        self.dhcp_lease.lock().clone()
+    }
+
+    /// Replaces the address and the default gateway obtained from the old lease with those from
+    /// the new lease.
+    pub(super) fn set_dhcp_lease(&self, lease: Option<DhcpLease>) {
+        let mut interface = self.interface.lock();
+        let mut dhcp_lease = self.dhcp_lease.lock();
+
+        if let Some(old_lease) = dhcp_lease.take() {
+            interface.update_ip_addrs(|ip_addrs| {
+                ip_addrs.retain(|ip_cidr| *ip_cidr != IpCidr::Ipv4(old_lease.ipv4_cidr));
+            });
+            if old_lease.router.is_some() {
+                interface.routes_mut().remove_default_ipv4_route();
+            }
+        }
+
+        if let Some(new_lease) = lease.as_ref() {
+            interface.update_ip_addrs(|ip_addrs| {
+                // If there are too many addresses, the lease cannot be applied.
+                let _ = ip_addrs.push(IpCidr::Ipv4(new_lease.ipv4_cidr));
+            });
+            if let Some(router) = new_lease.router {
+                let _ = interface.routes_mut().add_default_ipv4_route(router);
+            }
+        }
+
+        *dhcp_lease = lease;
+    }
+
+    pub(super) fn ipv4_routes(&self) -> Vec<(Ipv4Cidr, Ipv4Address)> {
+        let mut ipv4_routes = Vec::new();
+
+        self.interface.lock().routes_mut().update(|routes| {
+            ipv4_routes.extend(routes.iter().filter_map(|route| {
+                match (route.cidr, route.via_router) {
+                    (IpCidr::Ipv4(ipv4_cidr), IpAddress::Ipv4(gateway)) => {
+                        Some((ipv4_cidr, gateway))
+                    }
+                    _ => None,
+                }
+            }));
+        });
+
+        ipv4_routes
+    }
+
+    pub(super) fn ipv6_routes(&self) -> Vec<(Ipv6Cidr, Ipv6Address)> {
+        let mut ipv6_routes = Vec::new();
+
+        self.interface.lock().routes_mut().update(|routes| {
+            ipv6_routes.extend(routes.iter().filter_map(|route| {
+                match (route.cidr, route.via_router) {
+                    (IpCidr::Ipv6(ipv6_cidr), IpAddress::Ipv6(gateway)) => {
+                        Some((ipv6_cidr, gateway))
+                    }
+                    _ => None,
+                }
+            }));
+        });
+
+        ipv6_routes
+    }
+
+    pub(super) fn add_route(
+        &self,
+        ip_cidr: IpCidr,
+        gateway: IpAddress,
+    ) -> Result<(), IfaceConfigError> {
+        let mut result = Ok(());
+
+        self.interface.lock().routes_mut().update(|routes| {
+            let route = Route {
+                cidr: ip_cidr,
+                via_router: gateway,
+                preferred_until: None,
+                expires_at: None,
+            };
+
+            if routes.iter().any(|old_route| old_route.cidr == route.cidr) {
+                result = Err(IfaceConfigError::Exists);
+            } else if routes.push(route).is_err() {
+                result = Err(IfaceConfigError::Full);
+            }
+        });
+
+        result
+    }
+}
+
+impl<E: Ext> IfaceCommon<E> {
+    /// Queues an IP packet to be sent in the next poll.
+    ///
+    /// The packet must start with a well-formed IPv4 or IPv6 header. The header will be emitted
+    /// again, so its checksum does not need to be valid.
+    pub(super) fn send_ip_packet(&self, packet: Vec<u8>) -> Result<(), SendIpPacketError> {
+        let is_well_formed = IpPacket::new_checked(packet.as_slice())
+            .is_some_and(|pkt| pkt.parse_repr_unchecked().is_some());
+        if !is_well_formed {
+            return Err(SendIpPacketError::Malformed);
+        }
+
+        let mut ip_packets = self.ip_packets.lock();
+        if ip_packets.len() >= MAX_PENDING_IP_PACKETS {
+            return Err(SendIpPacketError::BufferFull);
+        }
+        ip_packets.push_back(packet);
+
+        Ok(())
+    }
+}
+
+impl<E: Ext> IfaceCommon<E> {
+    /// Joins the IPv4 multicast group.
+    ///
+    /// The IGMP report is sent when the iface is polled next time.
+    pub(super) fn join_multicast_group(&self, group: Ipv4Address) {
+        self.multicast_groups.lock().join(group);
+    }
+
+    /// Leaves the IPv4 multicast group.
+    ///
+    /// The IGMP leave message is sent when the iface is polled next time.
+    pub(super) fn leave_multicast_group(&self, group: Ipv4Address) {
+        self.multicast_groups.lock().leave(group);
+    }
+
+    /// Returns the IPv4 multicast groups that the device should receive, if they have changed
+    /// since the last call.
+    pub(super) fn take_multicast_filter_update(&self) -> Option<Vec<Ipv4Address>> {
+        self.multicast_groups.lock().take_filter_update()
+    }
+}
+
+// Lock order: interface -> sockets -> ip_packets -> multicast_groups
 impl<E: Ext> IfaceCommon<E> {
     /// Acquires the lock to the interface.
     pub(crate) fn interface(&self) -> SpinLockGuard<smoltcp::iface::Interface, LocalIrqDisabled> {
@@ -85,10 +313,15 @@ impl<E: Ext> IfaceCommon<E> {
     pub(super) fn bind(
         &self,
         iface: Arc<dyn Iface<E>>,
+        ip_version: IpVersion,
         config: BindPortConfig,
     ) -> core::result::Result<BoundPort<E>, BindError> {
         let port = self.bind_port(config)?;
-        Ok(BoundPort { iface, port })
+        Ok(BoundPort {
+            iface,
+            ip_version,
+            port,
+        })
     }
 
     /// Allocates an unused ephemeral port.
@@ -145,6 +378,24 @@ impl<E: Ext> IfaceCommon<E> {
 }
 
 impl<E: Ext> IfaceCommon<E> {
+    pub(super) fn tcp_socket_infos(&self) -> Vec<TcpSocketInfo> {
+        let sockets = self.sockets.lock();
+
+        let listener_infos = sockets
+            .tcp_listener_iter()
+            .filter_map(|socket| socket.info());
+        let conn_infos = sockets.tcp_conn_iter().filter_map(|socket| socket.info());
+        listener_infos.chain(conn_infos).collect()
+    }
+
+    pub(super) fn udp_socket_infos(&self) -> Vec<UdpSocketInfo> {
+        self.sockets
+            .lock()
+            .udp_socket_iter()
+            .filter_map(|socket| socket.info())
+            .collect()
+    }
+
     pub(crate) fn register_udp_socket(&self, socket: Arc<UdpSocketBg<E>>) {
         let mut sockets = self.sockets.lock();
         sockets.insert_udp_socket(socket);
@@ -176,7 +427,7 @@ impl<E: Ext> IfaceCommon<E> {
             &'pkt [u8],
             &'cx mut Context,
             D::TxToken<'tx>,
-            Option<(Ipv4Packet<&'pkt [u8]>, D::TxToken<'tx>)>,
+            Option<(IpPacket<&'pkt [u8]>, D::TxToken<'tx>)>,
         >,
         Q: FnMut(&Packet, &mut Context, D::TxToken<'_>),
     {
@@ -184,13 +435,35 @@ impl<E: Ext> IfaceCommon<E> {
         interface.context().now = get_network_timestamp();
 
         let mut sockets = self.sockets.lock();
+        let mut ip_packets = self.ip_packets.lock();
+        let mut multicast_groups = self.multicast_groups.lock();
+
+        // All the packets sent to the device are generated by the local host, so they must pass
+        // through the output hook.
+        let mut dispatch_phy = |pkt: &Packet, cx: &mut Context, tx_token: D::TxToken<'_>| {
+            let verdict = self
+                .filter_ip_packet
+                .filter_ip_packet(FilterHook::Output, &FilterPacket::of_packet(pkt));
+            if verdict == FilterVerdict::Accept {
+                self.stats.count_outgoing(pkt);
+                dispatch_phy(pkt, cx, tx_token);
+            }
+        };
 
         loop {
             let mut new_tcp_conns = Vec::new();
 
-            let mut context = PollContext::new(interface.context(), &sockets, &mut new_tcp_conns);
+            let mut context = PollContext::new(
+                interface.context(),
+                &sockets,
+                &mut new_tcp_conns,
+                &mut multicast_groups,
+                &self.tap_ip_packet,
+                &self.filter_ip_packet,
+                &self.stats,
+            );
             context.poll_ingress(device, &mut process_phy, &mut dispatch_phy);
-            context.poll_egress(device, &mut dispatch_phy);
+            context.poll_egress(device, &mut dispatch_phy, &mut ip_packets);
 
             // New packets sent by new connections are not handled. So if there are new
             // connections, try again.
@@ -200,10 +473,14 @@ impl<E: Ext> IfaceCommon<E> {
                 new_tcp_conns.into_iter().for_each(|tcp_conn| {
                     let res = sockets.insert_connection(tcp_conn);
                     debug_assert!(res.is_ok());
+                    self.stats.inc(SnmpCounter::TcpPassiveOpens);
                 });
             }
         }
 
+        drop(multicast_groups);
+        drop(ip_packets);
+
         sockets.remove_dead_tcp_connections();
 
         for socket in sockets.tcp_listener_iter() {
@@ -240,6 +517,7 @@ impl<E: Ext> IfaceCommon<E> {
 // FIXME: TCP and UDP ports are independent. Find a way to track the protocol here.
 pub struct BoundPort<E: Ext> {
     iface: Arc<dyn Iface<E>>,
+    ip_version: IpVersion,
     port: u16,
 }
 
@@ -249,6 +527,11 @@ impl<E: Ext> BoundPort<E> {
         &self.iface
     }
 
+    /// Returns the IP version of the bound address.
+    pub fn ip_version(&self) -> IpVersion {
+        self.ip_version
+    }
+
     /// Returns the port number.
     pub fn port(&self) -> u16 {
         self.port
@@ -256,9 +539,9 @@ impl<E: Ext> BoundPort<E> {
 
     /// Returns the bound endpoint.
     pub fn endpoint(&self) -> Option<IpEndpoint> {
-        let ip_addr = {
-            let ipv4_addr = self.iface().ipv4_addr()?;
-            IpAddress::Ipv4(ipv4_addr)
+        let ip_addr = match self.ip_version {
+            IpVersion::Ipv4 => IpAddress::Ipv4(self.iface().ipv4_addr()?),
+            IpVersion::Ipv6 => IpAddress::Ipv6(self.iface().ipv6_addr()?),
         };
         Some(IpEndpoint::new(ip_addr, self.port))
     }
@@ -0,0 +1,290 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! The DHCPv4 client.
+//!
+//! The client obtains an IPv4 address, a default gateway, and DNS servers from a DHCP server,
+//! and renews the lease before it expires. See
+//! <https://datatracker.ietf.org/doc/html/rfc2131>.
+
+use alloc::vec::Vec;
+
+use smoltcp::{
+    time::{Duration, Instant},
+    wire::{DhcpMessageType, DhcpRepr, EthernetAddress, Ipv4Address, Ipv4AddressExt, Ipv4Cidr},
+};
+
+/// A lease obtained from a DHCP server.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DhcpLease {
+    /// The assigned address, along with the subnet prefix length.
+    pub ipv4_cidr: Ipv4Cidr,
+    /// The default gateway.
+    pub router: Option<Ipv4Address>,
+    /// The DNS servers.
+    pub dns_servers: Vec<Ipv4Address>,
+    /// The address of the DHCP server.
+    pub server_addr: Ipv4Address,
+}
+
+pub(super) struct DhcpClient {
+    ether_addr: EthernetAddress,
+    state: State,
+    transaction_id: u32,
+    /// The time to send the next message.
+    retry_at: Instant,
+    /// The number of messages that have been sent without receiving a reply.
+    retry_count: u32,
+    /// The lease change that has not been applied to the iface.
+    lease_update: Option<Option<DhcpLease>>,
+}
+
+enum State {
+    /// Broadcasting DHCPDISCOVER messages to find a server.
+    Discovering,
+    /// Broadcasting DHCPREQUEST messages to request the address offered by the server.
+    Requesting {
+        server_addr: Ipv4Address,
+        requested_addr: Ipv4Address,
+    },
+    /// Holding a valid lease.
+    Bound(BoundLease),
+    /// Broadcasting DHCPREQUEST messages to extend the lease.
+    Renewing(BoundLease),
+}
+
+struct BoundLease {
+    lease: DhcpLease,
+    renew_at: Instant,
+    expires_at: Instant,
+}
+
+/// The options requested from the server: the subnet mask, the router, and the DNS servers.
+const PARAMETER_REQUEST_LIST: &[u8] = &[1, 3, 6];
+
+/// The initial interval to retransmit messages, which is doubled after each retransmission.
+///
+/// See <https://datatracker.ietf.org/doc/html/rfc2131#section-4.1>.
+const INITIAL_RETRY_INTERVAL: Duration = Duration::from_secs(4);
+const MAX_RETRY_INTERVAL: Duration = Duration::from_secs(64);
+
+/// The number of DHCPREQUEST messages to send before restarting the discovery.
+const MAX_REQUEST_RETRIES: u32 = 3;
+
+/// The lease duration if the server does not specify one.
+const DEFAULT_LEASE_DURATION: u32 = 3600;
+
+impl DhcpClient {
+    pub(super) fn new(ether_addr: EthernetAddress, now: Instant) -> Self {
+        let octets = ether_addr.as_bytes();
+        // There is no random number generator here. The transaction ID only needs to differ
+        // among the clients, so the Ethernet address and the current time are mixed instead.
+        let transaction_id = u32::from_be_bytes([octets[2], octets[3], octets[4], octets[5]])
+            ^ (now.total_millis() as u32);
+
+        Self {
+            ether_addr,
+            state: State::Discovering,
+            transaction_id,
+            retry_at: now,
+            retry_count: 0,
+            lease_update: None,
+        }
+    }
+
+    /// Returns the time at which [`Self::poll`] should be called.
+    pub(super) fn poll_at(&self) -> Instant {
+        match &self.state {
+            State::Bound(bound) => bound.renew_at,
+            State::Renewing(bound) => self.retry_at.min(bound.expires_at),
+            State::Discovering | State::Requesting { .. } => self.retry_at,
+        }
+    }
+
+    /// Takes the lease change that should be applied to the iface.
+    pub(super) fn take_lease_update(&mut self) -> Option<Option<DhcpLease>> {
+        self.lease_update.take()
+    }
+
+    /// Updates the timers and returns the message that should be sent, if any.
+    pub(super) fn poll(&mut self, now: Instant) -> Option<DhcpRepr<'static>> {
+        match &self.state {
+            State::Bound(bound) if now >= bound.renew_at => {
+                let State::Bound(bound) = core::mem::replace(&mut self.state, State::Discovering)
+                else {
+                    unreachable!();
+                };
+                self.state = State::Renewing(bound);
+                self.restart_transaction(now);
+            }
+            State::Renewing(bound) if now >= bound.expires_at => {
+                self.lease_update = Some(None);
+                self.state = State::Discovering;
+                self.restart_transaction(now);
+            }
+            State::Requesting { .. } if now >= self.retry_at => {
+                if self.retry_count >= MAX_REQUEST_RETRIES {
+                    self.state = State::Discovering;
+                    self.restart_transaction(now);
+                }
+            }
+            _ => (),
+        }
+
+        if matches!(self.state, State::Bound(_)) || now < self.retry_at {
+            return None;
+        }
+
+        let interval = INITIAL_RETRY_INTERVAL * (1 << self.retry_count.min(4));
+        self.retry_at = now + interval.min(MAX_RETRY_INTERVAL);
+        self.retry_count += 1;
+
+        Some(self.new_message())
+    }
+
+    /// Processes a message from a DHCP server.
+    pub(super) fn process(&mut self, repr: &DhcpRepr, now: Instant) {
+        // Ignore the message if it is not a reply to our current transaction.
+        if repr.transaction_id != self.transaction_id
+            || repr.client_hardware_address != self.ether_addr
+        {
+            return;
+        }
+
+        match (&self.state, repr.message_type) {
+            (State::Discovering, DhcpMessageType::Offer) => {
+                let Some(server_addr) = repr.server_identifier else {
+                    return;
+                };
+                if !repr.your_ip.x_is_unicast() {
+                    return;
+                }
+
+                self.state = State::Requesting {
+                    server_addr,
+                    requested_addr: repr.your_ip,
+                };
+                self.retry_at = now;
+                self.retry_count = 0;
+            }
+            (State::Requesting { server_addr, .. }, DhcpMessageType::Ack) => {
+                let server_addr = *server_addr;
+                self.bind(repr, server_addr, now);
+            }
+            (State::Renewing(bound), DhcpMessageType::Ack) => {
+                let server_addr = repr.server_identifier.unwrap_or(bound.lease.server_addr);
+                self.bind(repr, server_addr, now);
+            }
+            (State::Requesting { .. }, DhcpMessageType::Nak) => {
+                self.state = State::Discovering;
+                self.restart_transaction(now);
+            }
+            (State::Renewing(_), DhcpMessageType::Nak) => {
+                self.lease_update = Some(None);
+                self.state = State::Discovering;
+                self.restart_transaction(now);
+            }
+            _ => (),
+        }
+    }
+
+    fn bind(&mut self, repr: &DhcpRepr, server_addr: Ipv4Address, now: Instant) {
+        // Ignore the acknowledgment if it does not tell us the subnet.
+        let Some(ipv4_cidr) = repr
+            .subnet_mask
+            .and_then(|subnet_mask| Ipv4Cidr::from_netmask(repr.your_ip, subnet_mask).ok())
+        else {
+            return;
+        };
+
+        let lease = DhcpLease {
+            ipv4_cidr,
+            router: repr.router,
+            dns_servers: repr
+                .dns_servers
+                .as_ref()
+                .map(|dns_servers| dns_servers.iter().copied().collect())
+                .unwrap_or_default(),
+            server_addr,
+        };
+
+        let lease_duration = repr.lease_duration.unwrap_or(DEFAULT_LEASE_DURATION);
+        // The default renewal time (T1) is half of the lease duration. See
+        // <https://datatracker.ietf.org/doc/html/rfc2131#section-4.4.5>.
+        let renew_duration = repr
+            .renew_duration
+            .unwrap_or(lease_duration / 2)
+            .min(lease_duration);
+
+        let is_changed = match &self.state {
+            State::Renewing(bound) => bound.lease != lease,
+            _ => true,
+        };
+        if is_changed {
+            self.lease_update = Some(Some(lease.clone()));
+        }
+
+        self.state = State::Bound(BoundLease {
+            lease,
+            renew_at: now + Duration::from_secs(renew_duration as u64),
+            expires_at: now + Duration::from_secs(lease_duration as u64),
+        });
+    }
+
+    fn restart_transaction(&mut self, now: Instant) {
+        self.transaction_id = self.transaction_id.wrapping_add(1);
+        self.retry_at = now;
+        self.retry_count = 0;
+    }
+
+    fn new_message(&self) -> DhcpRepr<'static> {
+        let (message_type, client_ip, requested_ip, server_identifier) = match &self.state {
+            State::Discovering => (
+                DhcpMessageType::Discover,
+                Ipv4Address::UNSPECIFIED,
+                None,
+                None,
+            ),
+            State::Requesting {
+                server_addr,
+                requested_addr,
+            } => (
+                DhcpMessageType::Request,
+                Ipv4Address::UNSPECIFIED,
+                Some(*requested_addr),
+                Some(*server_addr),
+            ),
+            // The request is broadcast like in the REBINDING state, instead of being sent to the
+            // server directly, so the server address does not need to be resolved.
+            State::Renewing(bound) | State::Bound(bound) => (
+                DhcpMessageType::Request,
+                bound.lease.ipv4_cidr.address(),
+                None,
+                None,
+            ),
+        };
+
+        DhcpRepr {
+            message_type,
+            transaction_id: self.transaction_id,
+            secs: 0,
+            client_hardware_address: self.ether_addr,
+            client_ip,
+            your_ip: Ipv4Address::UNSPECIFIED,
+            server_ip: Ipv4Address::UNSPECIFIED,
+            router: None,
+            subnet_mask: None,
+            relay_agent_ip: Ipv4Address::UNSPECIFIED,
+            broadcast: false,
+            requested_ip,
+            client_identifier: Some(self.ether_addr),
+            server_identifier,
+            parameter_request_list: Some(PARAMETER_REQUEST_LIST),
+            dns_servers: None,
+            max_size: None,
+            lease_duration: None,
+            renew_duration: None,
+            rebind_duration: None,
+            additional_options: &[],
+        }
+    }
+}
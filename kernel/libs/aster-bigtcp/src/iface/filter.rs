@@ -0,0 +1,105 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! Netfilter-style hooks for IP packets.
+//!
+//! An iface asks its packet filter whether to accept an IP packet at the following hooks:
+//!  - [`FilterHook::Prerouting`]: when a packet is received, before it is known whether the packet
+//!    is destined to the local host;
+//!  - [`FilterHook::Input`]: when a packet is going to be delivered to the local host;
+//!  - [`FilterHook::Output`]: when a packet is generated by the local host.
+//!
+//! A packet that is sent to the local host passes through [`FilterHook::Output`],
+//! [`FilterHook::Prerouting`], and [`FilterHook::Input`] in turn, like Linux.
+
+use smoltcp::{
+    iface::packet::{IpPayload, Packet},
+    wire::{IpAddress, IpProtocol, IpRepr},
+};
+
+/// The hook where an IP packet is filtered.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FilterHook {
+    Prerouting,
+    Input,
+    Output,
+}
+
+/// The verdict of a packet filter.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FilterVerdict {
+    /// The packet continues to be processed.
+    Accept,
+    /// The packet is dropped silently.
+    Drop,
+}
+
+/// The fields of an IP packet that a packet filter can match.
+#[derive(Debug, Clone, Copy)]
+pub struct FilterPacket {
+    pub src_addr: IpAddress,
+    pub dst_addr: IpAddress,
+    pub protocol: IpProtocol,
+    /// The source port and the destination port, if the packet is a TCP or UDP packet.
+    pub ports: Option<(u16, u16)>,
+}
+
+/// A trait to provide the `filter_ip_packet` method for ifaces.
+pub trait FilterIpPacket: Send + Sync {
+    /// Filters an IP packet at the hook.
+    ///
+    /// The implementation must not poll the iface, since the iface is being polled when this
+    /// method is invoked.
+    fn filter_ip_packet(&self, hook: FilterHook, packet: &FilterPacket) -> FilterVerdict;
+}
+
+impl FilterPacket {
+    /// Extracts the fields from the IP header and the IP payload of a received packet.
+    pub(super) fn parse(ip_repr: &IpRepr, ip_payload: &[u8]) -> Self {
+        let protocol = ip_repr.next_header();
+
+        // Both the TCP header and the UDP header start with the source port and the destination
+        // port.
+        let ports = match protocol {
+            IpProtocol::Tcp | IpProtocol::Udp if ip_payload.len() >= 4 => Some((
+                u16::from_be_bytes([ip_payload[0], ip_payload[1]]),
+                u16::from_be_bytes([ip_payload[2], ip_payload[3]]),
+            )),
+            _ => None,
+        };
+
+        Self {
+            src_addr: ip_repr.src_addr(),
+            dst_addr: ip_repr.dst_addr(),
+            protocol,
+            ports,
+        }
+    }
+
+    /// Extracts the fields from a packet that is generated by the local host.
+    pub(super) fn of_packet(pkt: &Packet) -> Self {
+        let ip_repr = pkt.ip_repr();
+
+        let ports = match pkt.payload() {
+            IpPayload::Tcp(tcp_repr) => Some((tcp_repr.src_port, tcp_repr.dst_port)),
+            IpPayload::Udp(udp_repr, _) => Some((udp_repr.src_port, udp_repr.dst_port)),
+            _ => None,
+        };
+
+        Self {
+            src_addr: ip_repr.src_addr(),
+            dst_addr: ip_repr.dst_addr(),
+            protocol: ip_repr.next_header(),
+            ports,
+        }
+    }
+
+    /// Extracts the fields from the IP header and the ports of a TCP or UDP packet.
+    pub(super) fn with_ports(ip_repr: &IpRepr, src_port: u16, dst_port: u16) -> Self {
+        Self {
+            src_addr: ip_repr.src_addr(),
+            dst_addr: ip_repr.dst_addr(),
+            protocol: ip_repr.next_header(),
+            ports: Some((src_port, dst_port)),
+        }
+    }
+}
@@ -1,11 +1,17 @@
 // SPDX-License-Identifier: MPL-2.0
 
-use alloc::sync::Arc;
+use alloc::{sync::Arc, vec::Vec};
 
-use smoltcp::wire::Ipv4Address;
+use smoltcp::wire::{
+    EthernetAddress, IpAddress, IpCidr, IpVersion, Ipv4Address, Ipv4Cidr, Ipv6Address, Ipv6Cidr,
+};
 
-use super::{port::BindPortConfig, BoundPort};
-use crate::{errors::BindError, ext::Ext};
+use super::{port::BindPortConfig, BoundPort, DhcpLease, SnmpCounter};
+use crate::{
+    errors::{BindError, IfaceConfigError, SendFrameError, SendIpPacketError},
+    ext::Ext,
+    socket::{TcpSocketInfo, UdpSocketInfo},
+};
 
 /// A network interface.
 ///
@@ -16,6 +22,15 @@ use crate::{errors::BindError, ext::Ext};
 pub trait Iface<E>: internal::IfaceInternal<E> + Send + Sync {
     /// Transmits or receives packets queued in the iface, and updates socket status accordingly.
     fn poll(&self);
+
+    /// Transmits a link-layer frame, bypassing the protocol stack of the iface.
+    ///
+    /// The frame must include the link-layer header. Only Ethernet ifaces support this; other
+    /// ifaces will fail with [`SendFrameError::NotSupported`]. The frame will not be passed to
+    /// [`TapFrame::tap_frame`].
+    ///
+    /// [`TapFrame::tap_frame`]: super::TapFrame::tap_frame
+    fn send_frame(&self, frame: &[u8]) -> Result<(), SendFrameError>;
 }
 
 impl<E: Ext> dyn Iface<E> {
@@ -25,17 +40,18 @@ impl<E: Ext> dyn Iface<E> {
     /// socket.
     ///
     /// If [`BindPortConfig::Ephemeral`] is specified, the iface will pick up an ephemeral port for
-    /// the socket.
+    /// the socket. The socket will use the iface address of the specified IP version.
     ///
     /// FIXME: The reason for binding the socket and the iface together is because there are
     /// limitations inside smoltcp. See discussion at
     /// <https://github.com/smoltcp-rs/smoltcp/issues/779>.
     pub fn bind(
         self: &Arc<Self>,
+        ip_version: IpVersion,
         config: BindPortConfig,
     ) -> core::result::Result<BoundPort<E>, BindError> {
         let common = self.common();
-        common.bind(self.clone(), config)
+        common.bind(self.clone(), ip_version, config)
     }
 
     /// Gets the name of the iface.
@@ -52,6 +68,118 @@ impl<E: Ext> dyn Iface<E> {
         self.common().ipv4_addr()
     }
 
+    /// Gets the IPv6 address of the iface, if any.
+    ///
+    /// FIXME: One iface may have multiple IPv6 addresses.
+    pub fn ipv6_addr(&self) -> Option<Ipv6Address> {
+        self.common().ipv6_addr()
+    }
+
+    /// Gets the hardware address of the iface, if it is an Ethernet iface.
+    pub fn hardware_addr(&self) -> Option<EthernetAddress> {
+        self.common().hardware_addr()
+    }
+
+    /// Gets all the IPv4 addresses of the iface, along with their subnet prefix lengths.
+    pub fn ipv4_cidrs(&self) -> Vec<Ipv4Cidr> {
+        self.common().ipv4_cidrs()
+    }
+
+    /// Assigns a new IPv4 address to the iface.
+    pub fn add_ipv4_cidr(&self, ipv4_cidr: Ipv4Cidr) -> Result<(), IfaceConfigError> {
+        self.common().add_ip_cidr(IpCidr::Ipv4(ipv4_cidr))
+    }
+
+    /// Gets all the IPv6 addresses of the iface, along with their subnet prefix lengths.
+    pub fn ipv6_cidrs(&self) -> Vec<Ipv6Cidr> {
+        self.common().ipv6_cidrs()
+    }
+
+    /// Assigns a new IPv6 address to the iface.
+    pub fn add_ipv6_cidr(&self, ipv6_cidr: Ipv6Cidr) -> Result<(), IfaceConfigError> {
+        self.common().add_ip_cidr(IpCidr::Ipv6(ipv6_cidr))
+    }
+
+    /// Gets the IPv4 routes of the iface, as pairs of the destination and the gateway.
+    ///
+    /// The routes to the subnets that the iface is directly connected to are not included.
+    pub fn ipv4_routes(&self) -> Vec<(Ipv4Cidr, Ipv4Address)> {
+        self.common().ipv4_routes()
+    }
+
+    /// Gets the IPv6 routes of the iface, as pairs of the destination and the gateway.
+    ///
+    /// The routes to the subnets that the iface is directly connected to are not included.
+    pub fn ipv6_routes(&self) -> Vec<(Ipv6Cidr, Ipv6Address)> {
+        self.common().ipv6_routes()
+    }
+
+    /// Adds an IPv4 route to the destination via the gateway.
+    pub fn add_ipv4_route(
+        &self,
+        ipv4_cidr: Ipv4Cidr,
+        gateway: Ipv4Address,
+    ) -> Result<(), IfaceConfigError> {
+        self.common()
+            .add_route(IpCidr::Ipv4(ipv4_cidr), IpAddress::Ipv4(gateway))
+    }
+
+    /// Adds an IPv6 route to the destination via the gateway.
+    pub fn add_ipv6_route(
+        &self,
+        ipv6_cidr: Ipv6Cidr,
+        gateway: Ipv6Address,
+    ) -> Result<(), IfaceConfigError> {
+        self.common()
+            .add_route(IpCidr::Ipv6(ipv6_cidr), IpAddress::Ipv6(gateway))
+    }
+
+    /// Joins the IPv4 multicast group.
+    ///
+    /// The iface counts how many times each group is joined, and it does not leave the group
+    /// until [`Self::leave_ipv4_multicast_group`] is called the same number of times. The iface
+    /// should be polled to report the new group to the multicast routers.
+    pub fn join_ipv4_multicast_group(&self, group: Ipv4Address) {
+        self.common().join_multicast_group(group)
+    }
+
+    /// Leaves the IPv4 multicast group.
+    ///
+    /// The iface should be polled to tell the multicast routers about the change.
+    pub fn leave_ipv4_multicast_group(&self, group: Ipv4Address) {
+        self.common().leave_multicast_group(group)
+    }
+
+    /// Gets the DHCP lease that the iface is configured with, if any.
+    pub fn dhcp_lease(&self) -> Option<DhcpLease> {
+        self.common().dhcp_lease()
+    }
+
+    /// Sends an IP packet, bypassing the transport layer of the iface.
+    ///
+    /// The packet must start with a well-formed IPv4 or IPv6 header, except that the header
+    /// checksum is ignored and computed again. The packet is queued and will be sent when the
+    /// iface is polled next time. If the destination is a local address, the packet will be
+    /// handled as if it is received by the iface.
+    pub fn send_ip_packet(&self, packet: Vec<u8>) -> Result<(), SendIpPacketError> {
+        self.common().send_ip_packet(packet)
+    }
+
+    /// Gets the value of the SNMP counter of the iface.
+    pub fn snmp_counter(&self, counter: SnmpCounter) -> u64 {
+        self.common().stats().get(counter)
+    }
+
+    /// Gets the information of the TCP connections and the TCP listeners of the iface.
+    pub fn tcp_socket_infos(&self) -> Vec<TcpSocketInfo> {
+        self.common().tcp_socket_infos()
+    }
+
+    /// Gets the information of the UDP sockets of the iface.
+    pub fn udp_socket_infos(&self) -> Vec<UdpSocketInfo> {
+        self.common().udp_socket_infos()
+    }
+
     /// Returns a reference to the associated [`ScheduleNextPoll`].
     pub fn sched_poll(&self) -> &E::ScheduleNextPoll {
         self.common().sched_poll()
@@ -1,16 +1,25 @@
 // SPDX-License-Identifier: MPL-2.0
 
 mod common;
+mod dhcp;
+mod filter;
 #[allow(clippy::module_inception)]
 mod iface;
+mod multicast;
 mod phy;
 mod poll;
 mod port;
 mod sched;
+mod stats;
+mod tap;
 mod time;
 
 pub use common::BoundPort;
+pub use dhcp::DhcpLease;
+pub use filter::{FilterHook, FilterIpPacket, FilterPacket, FilterVerdict};
 pub use iface::Iface;
-pub use phy::{EtherIface, IpIface};
+pub use phy::{EtherIface, IpIface, Ipv4Config};
 pub use port::BindPortConfig;
 pub use sched::ScheduleNextPoll;
+pub use stats::SnmpCounter;
+pub use tap::{FrameDirection, TapFrame, TapIpPacket};
@@ -0,0 +1,216 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! IPv4 multicast group management.
+//!
+//! An iface keeps track of the IPv4 multicast groups that it has joined. The packets sent to
+//! other groups are ignored. The iface also tells the multicast routers about its groups via
+//! IGMPv2, which is described in <https://datatracker.ietf.org/doc/html/rfc2236>.
+
+use alloc::{
+    collections::{btree_map::BTreeMap, vec_deque::VecDeque},
+    vec,
+    vec::Vec,
+};
+
+use smoltcp::wire::Ipv4Address;
+
+/// The all-systems multicast group, which all the hosts join implicitly.
+const IPV4_ALL_SYSTEMS: Ipv4Address = Ipv4Address::new(224, 0, 0, 1);
+
+/// The all-routers multicast group, which the leave messages are sent to.
+const IPV4_ALL_ROUTERS: Ipv4Address = Ipv4Address::new(224, 0, 0, 2);
+
+/// The length of an IGMPv2 message.
+pub(super) const IGMP_MSG_LEN: usize = 8;
+
+/// The hop limit of IGMP messages.
+pub(super) const IGMP_HOP_LIMIT: u8 = 1;
+
+const IGMP_MEMBERSHIP_QUERY: u8 = 0x11;
+const IGMP_V2_MEMBERSHIP_REPORT: u8 = 0x16;
+const IGMP_LEAVE_GROUP: u8 = 0x17;
+
+/// The IPv4 multicast groups joined by an iface.
+pub(super) struct MulticastGroups {
+    /// The joined groups, along with the number of times that they are joined.
+    groups: BTreeMap<Ipv4Address, usize>,
+    /// The IGMP messages that have not been sent.
+    pending_msgs: VecDeque<IgmpMsg>,
+    /// Whether the groups have changed since the multicast filter of the device was updated.
+    is_filter_dirty: bool,
+}
+
+/// An IGMP message to send.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(super) enum IgmpMsg {
+    Report(Ipv4Address),
+    Leave(Ipv4Address),
+}
+
+impl MulticastGroups {
+    pub(super) const fn new() -> Self {
+        Self {
+            groups: BTreeMap::new(),
+            pending_msgs: VecDeque::new(),
+            // The device should know the all-systems group from the beginning.
+            is_filter_dirty: true,
+        }
+    }
+
+    /// Joins the group.
+    ///
+    /// If the group is joined for the first time, an unsolicited report will be sent.
+    //
+    // TODO: RFC 2236 suggests repeating the unsolicited report once or twice after a short
+    // delay, in case the initial one is lost.
+    pub(super) fn join(&mut self, group: Ipv4Address) {
+        let count = self.groups.entry(group).or_insert(0);
+        *count += 1;
+        if *count != 1 || group == IPV4_ALL_SYSTEMS {
+            return;
+        }
+
+        self.queue_msg(IgmpMsg::Report(group));
+        self.is_filter_dirty = true;
+    }
+
+    /// Leaves the group.
+    ///
+    /// If the group is left as many times as it is joined, a leave message will be sent.
+    pub(super) fn leave(&mut self, group: Ipv4Address) {
+        let Some(count) = self.groups.get_mut(&group) else {
+            return;
+        };
+        *count -= 1;
+        if *count != 0 {
+            return;
+        }
+
+        self.groups.remove(&group);
+        if group == IPV4_ALL_SYSTEMS {
+            return;
+        }
+
+        // The pending reports are no longer valid.
+        self.pending_msgs
+            .retain(|msg| *msg != IgmpMsg::Report(group));
+        self.queue_msg(IgmpMsg::Leave(group));
+        self.is_filter_dirty = true;
+    }
+
+    /// Returns whether the group is joined.
+    pub(super) fn contains(&self, group: &Ipv4Address) -> bool {
+        *group == IPV4_ALL_SYSTEMS || self.groups.contains_key(group)
+    }
+
+    /// Processes an incoming IGMP message.
+    ///
+    /// A membership query is answered by the reports of the queried groups. Other messages are
+    /// ignored.
+    //
+    // TODO: RFC 2236 requires delaying the reports by a random time up to the maximum response
+    // time in the query, and suppressing a report if another host reports the same group first.
+    // For now, the reports are sent in the next poll.
+    pub(super) fn process_igmp(&mut self, msg: &[u8]) {
+        if msg.len() < IGMP_MSG_LEN || checksum(&msg[..IGMP_MSG_LEN]) != 0 {
+            return;
+        }
+        if msg[0] != IGMP_MEMBERSHIP_QUERY {
+            return;
+        }
+
+        let group = Ipv4Address::new(msg[4], msg[5], msg[6], msg[7]);
+        let queried_groups: Vec<Ipv4Address> = if group.is_unspecified() {
+            // This is a general query.
+            self.groups.keys().copied().collect()
+        } else if self.groups.contains_key(&group) {
+            // This is a group-specific query.
+            vec![group]
+        } else {
+            return;
+        };
+
+        for group in queried_groups {
+            if group != IPV4_ALL_SYSTEMS {
+                self.queue_msg(IgmpMsg::Report(group));
+            }
+        }
+    }
+
+    /// Takes the next IGMP message that should be sent.
+    pub(super) fn pop_igmp_msg(&mut self) -> Option<IgmpMsg> {
+        self.pending_msgs.pop_front()
+    }
+
+    /// Returns the groups that the multicast filter of the device should accept, if they have
+    /// changed since the last call.
+    pub(super) fn take_filter_update(&mut self) -> Option<Vec<Ipv4Address>> {
+        if !self.is_filter_dirty {
+            return None;
+        }
+        self.is_filter_dirty = false;
+
+        let mut groups: Vec<Ipv4Address> = self.groups.keys().copied().collect();
+        if !self.groups.contains_key(&IPV4_ALL_SYSTEMS) {
+            groups.push(IPV4_ALL_SYSTEMS);
+        }
+        Some(groups)
+    }
+
+    /// Marks the multicast filter of the device as outdated.
+    ///
+    /// This should be called if the filter needs to accept other addresses (e.g., the
+    /// solicited-node multicast addresses of new IPv6 addresses).
+    pub(super) fn mark_filter_dirty(&mut self) {
+        self.is_filter_dirty = true;
+    }
+
+    fn queue_msg(&mut self, msg: IgmpMsg) {
+        if !self.pending_msgs.contains(&msg) {
+            self.pending_msgs.push_back(msg);
+        }
+    }
+}
+
+impl IgmpMsg {
+    /// Returns the destination address of the message.
+    pub(super) fn dst_addr(&self) -> Ipv4Address {
+        match self {
+            Self::Report(group) => *group,
+            Self::Leave(_) => IPV4_ALL_ROUTERS,
+        }
+    }
+
+    /// Emits the IGMPv2 message.
+    //
+    // TODO: RFC 2236 requires the IP header to carry the Router Alert option.
+    pub(super) fn emit(&self) -> [u8; IGMP_MSG_LEN] {
+        let (msg_type, group) = match self {
+            Self::Report(group) => (IGMP_V2_MEMBERSHIP_REPORT, group),
+            Self::Leave(group) => (IGMP_LEAVE_GROUP, group),
+        };
+
+        let mut msg = [0; IGMP_MSG_LEN];
+        msg[0] = msg_type;
+        msg[4..8].copy_from_slice(&group.octets());
+
+        let checksum = checksum(&msg);
+        msg[2..4].copy_from_slice(&checksum.to_be_bytes());
+
+        msg
+    }
+}
+
+/// Computes the Internet checksum.
+///
+/// See <https://datatracker.ietf.org/doc/html/rfc1071>.
+fn checksum(data: &[u8]) -> u16 {
+    let mut sum = data
+        .chunks(2)
+        .map(|chunk| u16::from_be_bytes([chunk[0], chunk.get(1).copied().unwrap_or(0)]) as u32)
+        .sum::<u32>();
+    while sum >> 16 != 0 {
+        sum = (sum & 0xffff) + (sum >> 16);
+    }
+    !(sum as u16)
+}
@@ -1,23 +1,34 @@
 // SPDX-License-Identifier: MPL-2.0
 
-use alloc::{collections::btree_map::BTreeMap, string::String, sync::Arc};
+use alloc::{collections::btree_map::BTreeMap, string::String, sync::Arc, vec, vec::Vec};
 
 use ostd::sync::{LocalIrqDisabled, SpinLock};
 use smoltcp::{
-    iface::{packet::Packet, Config, Context},
-    phy::{DeviceCapabilities, TxToken},
+    iface::{
+        packet::{IpPayload, Packet},
+        Config, Context,
+    },
+    phy::{Device, DeviceCapabilities, Medium, TxToken},
     wire::{
-        self, ArpOperation, ArpPacket, ArpRepr, EthernetAddress, EthernetFrame, EthernetProtocol,
-        EthernetRepr, IpAddress, Ipv4Address, Ipv4AddressExt, Ipv4Cidr, Ipv4Packet,
+        self, ArpOperation, ArpPacket, ArpRepr, DhcpPacket, DhcpRepr, EthernetAddress,
+        EthernetFrame, EthernetProtocol, EthernetRepr, HardwareAddress, Icmpv6Packet, Icmpv6Repr,
+        IpAddress, IpProtocol, Ipv4Address, Ipv4AddressExt, Ipv4Cidr, Ipv4Packet, Ipv4Repr,
+        Ipv6Address, Ipv6Packet, Ipv6Repr, NdiscNeighborFlags, NdiscRepr, RawHardwareAddress,
+        UdpPacket, UdpRepr, DHCP_CLIENT_PORT, DHCP_SERVER_PORT,
     },
 };
 
 use crate::{
-    device::{NotifyDevice, WithDevice},
+    device::{FilterMulticast, NotifyDevice, WithDevice},
+    errors::SendFrameError,
     ext::Ext,
     iface::{
-        common::IfaceCommon, iface::internal::IfaceInternal, time::get_network_timestamp, Iface,
-        ScheduleNextPoll,
+        common::IfaceCommon,
+        dhcp::DhcpClient,
+        iface::internal::IfaceInternal,
+        poll::{solicited_node_addr, IpPacket, IPV6_LINK_LOCAL_ALL_NODES},
+        time::get_network_timestamp,
+        FrameDirection, Iface, ScheduleNextPoll, TapFrame,
     },
 };
 
@@ -25,41 +36,90 @@ pub struct EtherIface<D, E: Ext> {
     driver: D,
     common: IfaceCommon<E>,
     ether_addr: EthernetAddress,
-    arp_table: SpinLock<BTreeMap<Ipv4Address, EthernetAddress>, LocalIrqDisabled>,
+    /// The mappings between the IP addresses and the Ethernet addresses of the neighbors, which
+    /// are learned from ARP (for IPv4) and neighbor discovery (for IPv6).
+    neighbor_table: SpinLock<BTreeMap<IpAddress, EthernetAddress>, LocalIrqDisabled>,
+    dhcp_client: Option<SpinLock<DhcpClient, LocalIrqDisabled>>,
+    tap_frame: E::TapFrame,
+}
+
+/// The IPv4 configuration of an Ethernet iface.
+#[derive(Debug, Clone, Copy)]
+pub enum Ipv4Config {
+    /// The address and the gateway are statically configured.
+    Static {
+        ipv4_cidr: Ipv4Cidr,
+        gateway: Ipv4Address,
+    },
+    /// The address and the gateway are obtained from a DHCP server.
+    Dhcp,
+    /// No address is configured when the iface is created.
+    ///
+    /// The addresses and the routes can be added later.
+    Unconfigured,
 }
 
+/// A message that resolves the Ethernet address of a neighbor or answers such a resolution.
+enum NeighborMsg {
+    Arp(ArpRepr),
+    Ndisc(EthernetRepr, Ipv6Repr, NdiscRepr<'static>),
+}
+
+/// The hop limit of neighbor discovery messages.
+///
+/// See <https://datatracker.ietf.org/doc/html/rfc4861#section-7.1.1>.
+const NDISC_HOP_LIMIT: u8 = 255;
+
+/// The hop limit of DHCP messages.
+const DHCP_HOP_LIMIT: u8 = 64;
+
 impl<D: WithDevice, E: Ext> EtherIface<D, E> {
+    #[allow(clippy::too_many_arguments)]
     pub fn new(
         driver: D,
         ether_addr: EthernetAddress,
-        ip_cidr: Ipv4Cidr,
-        gateway: Ipv4Address,
+        ipv4_config: Ipv4Config,
         name: String,
         sched_poll: E::ScheduleNextPoll,
+        tap_frame: E::TapFrame,
+        tap_ip_packet: E::TapIpPacket,
+        filter_ip_packet: E::FilterIpPacket,
     ) -> Arc<Self> {
         let interface = driver.with(|device| {
             let config = Config::new(wire::HardwareAddress::Ethernet(ether_addr));
             let now = get_network_timestamp();
 
             let mut interface = smoltcp::iface::Interface::new(config, device, now);
-            interface.update_ip_addrs(|ip_addrs| {
-                debug_assert!(ip_addrs.is_empty());
-                ip_addrs.push(wire::IpCidr::Ipv4(ip_cidr)).unwrap();
-            });
-            interface
-                .routes_mut()
-                .add_default_ipv4_route(gateway)
-                .unwrap();
+            if let Ipv4Config::Static { ipv4_cidr, gateway } = ipv4_config {
+                interface.update_ip_addrs(|ip_addrs| {
+                    debug_assert!(ip_addrs.is_empty());
+                    ip_addrs.push(wire::IpCidr::Ipv4(ipv4_cidr)).unwrap();
+                });
+                interface
+                    .routes_mut()
+                    .add_default_ipv4_route(gateway)
+                    .unwrap();
+            }
             interface
         });
 
-        let common = IfaceCommon::new(name, interface, sched_poll);
+        let common = IfaceCommon::new(name, interface, sched_poll, tap_ip_packet, filter_ip_packet);
+
+        let dhcp_client = match ipv4_config {
+            Ipv4Config::Static { .. } | Ipv4Config::Unconfigured => None,
+            Ipv4Config::Dhcp => Some(SpinLock::new(DhcpClient::new(
+                ether_addr,
+                get_network_timestamp(),
+            ))),
+        };
 
         Arc::new(Self {
             driver,
             common,
             ether_addr,
-            arp_table: SpinLock::new(BTreeMap::new()),
+            neighbor_table: SpinLock::new(BTreeMap::new()),
+            dhcp_client,
+            tap_frame,
         })
     }
 }
@@ -72,7 +132,7 @@ impl<D, E: Ext> IfaceInternal<E> for EtherIface<D, E> {
 
 impl<D: WithDevice + 'static, E: Ext> Iface<E> for EtherIface<D, E>
 where
-    D::Device: NotifyDevice,
+    D::Device: NotifyDevice + FilterMulticast,
 {
     fn poll(&self) {
         self.driver.with(|device| {
@@ -81,10 +141,31 @@ where
                 |data, iface_cx, tx_token| self.process(data, iface_cx, tx_token),
                 |pkt, iface_cx, tx_token| self.dispatch(pkt, iface_cx, tx_token),
             );
+            let next_poll = match (next_poll, self.poll_dhcp(&mut *device)) {
+                (Some(next_poll), Some(next_dhcp_poll)) => Some(next_poll.min(next_dhcp_poll)),
+                (next_poll, next_dhcp_poll) => next_poll.or(next_dhcp_poll),
+            };
+            self.update_multicast_filter(&mut *device);
             device.notify_poll_end();
             self.common.sched_poll().schedule_next_poll(next_poll);
         });
     }
+
+    fn send_frame(&self, frame: &[u8]) -> Result<(), SendFrameError> {
+        self.driver.with(|device| {
+            if frame.len() > device.capabilities().max_transmission_unit {
+                return Err(SendFrameError::TooLarge);
+            }
+
+            let tx_token = device
+                .transmit(get_network_timestamp())
+                .ok_or(SendFrameError::BufferFull)?;
+            tx_token.consume(frame.len(), |buffer| buffer.copy_from_slice(frame));
+            device.notify_poll_end();
+
+            Ok(())
+        })
+    }
 }
 
 impl<D, E: Ext> EtherIface<D, E> {
@@ -93,40 +174,59 @@ impl<D, E: Ext> EtherIface<D, E> {
         data: &'pkt [u8],
         iface_cx: &mut Context,
         tx_token: T,
-    ) -> Option<(Ipv4Packet<&'pkt [u8]>, T)> {
-        match self.parse_ip_or_process_arp(data, iface_cx) {
+    ) -> Option<(IpPacket<&'pkt [u8]>, T)> {
+        self.tap_frame.tap_frame(data, FrameDirection::Incoming);
+
+        match self.parse_ip_or_process_neighbor(data, iface_cx) {
             Ok(pkt) => Some((pkt, tx_token)),
-            Err(Some(arp)) => {
-                Self::emit_arp(&arp, tx_token);
+            Err(Some(msg)) => {
+                self.emit_neighbor_msg(&msg, &iface_cx.caps, tx_token);
                 None
             }
             Err(None) => None,
         }
     }
 
-    fn parse_ip_or_process_arp<'pkt>(
+    fn parse_ip_or_process_neighbor<'pkt>(
         &self,
         data: &'pkt [u8],
         iface_cx: &mut Context,
-    ) -> Result<Ipv4Packet<&'pkt [u8]>, Option<ArpRepr>> {
+    ) -> Result<IpPacket<&'pkt [u8]>, Option<NeighborMsg>> {
         // Parse the Ethernet header. Ignore the packet if the header is ill-formed.
         let frame = EthernetFrame::new_checked(data).map_err(|_| None)?;
         let repr = EthernetRepr::parse(&frame).map_err(|_| None)?;
 
-        // Ignore the Ethernet frame if it is not sent to us.
-        if !repr.dst_addr.is_broadcast() && repr.dst_addr != self.ether_addr {
+        // Ignore the Ethernet frame if it is not sent to us. Multicast frames are accepted here
+        // and will be filtered by the IP layer, since IPv6 relies on multicast.
+        if !repr.dst_addr.is_broadcast()
+            && !repr.dst_addr.is_multicast()
+            && repr.dst_addr != self.ether_addr
+        {
             return Err(None);
         }
 
         // Ignore the Ethernet frame if the protocol is not supported.
         match repr.ethertype {
             EthernetProtocol::Ipv4 => {
-                Ok(Ipv4Packet::new_checked(frame.payload()).map_err(|_| None)?)
+                let pkt = Ipv4Packet::new_checked(frame.payload()).map_err(|_| None)?;
+                if self.process_dhcp(&pkt, iface_cx) {
+                    return Err(None);
+                }
+                Ok(IpPacket::Ipv4(pkt))
+            }
+            EthernetProtocol::Ipv6 => {
+                let pkt = Ipv6Packet::new_checked(frame.payload()).map_err(|_| None)?;
+                match Self::parse_ndisc(&pkt, iface_cx) {
+                    Some((ipv6_repr, ndisc_repr)) => {
+                        Err(self.process_ndisc(&ipv6_repr, &ndisc_repr, iface_cx))
+                    }
+                    None => Ok(IpPacket::Ipv6(pkt)),
+                }
             }
             EthernetProtocol::Arp => {
                 let pkt = ArpPacket::new_checked(frame.payload()).map_err(|_| None)?;
                 let arp = ArpRepr::parse(&pkt).map_err(|_| None)?;
-                Err(self.process_arp(&arp, iface_cx))
+                Err(self.process_arp(&arp, iface_cx).map(NeighborMsg::Arp))
             }
             _ => Err(None),
         }
@@ -150,9 +250,10 @@ impl<D, E: Ext> EtherIface<D, E> {
                 // Insert the mapping between the Ethernet address and the IP address.
                 //
                 // TODO: Remove the mapping if it expires.
-                self.arp_table
-                    .lock()
-                    .insert(*source_protocol_addr, *source_hardware_addr);
+                self.neighbor_table.lock().insert(
+                    IpAddress::Ipv4(*source_protocol_addr),
+                    *source_hardware_addr,
+                );
 
                 None
             }
@@ -188,52 +289,391 @@ impl<D, E: Ext> EtherIface<D, E> {
         }
     }
 
+    /// Parses the IPv6 packet as a neighbor discovery message.
+    ///
+    /// This method returns `None` if the packet is not a valid neighbor discovery message.
+    fn parse_ndisc<'pkt>(
+        pkt: &Ipv6Packet<&'pkt [u8]>,
+        iface_cx: &Context,
+    ) -> Option<(Ipv6Repr, NdiscRepr<'pkt>)> {
+        let ipv6_repr = Ipv6Repr::parse(pkt).ok()?;
+
+        // Neighbor discovery messages that may come from other links must be ignored. See
+        // <https://datatracker.ietf.org/doc/html/rfc4861#section-7.1.1>.
+        if ipv6_repr.next_header != IpProtocol::Icmpv6 || ipv6_repr.hop_limit != NDISC_HOP_LIMIT {
+            return None;
+        }
+
+        let icmp_pkt = Icmpv6Packet::new_checked(pkt.payload()).ok()?;
+        let icmp_repr = Icmpv6Repr::parse(
+            &ipv6_repr.src_addr,
+            &ipv6_repr.dst_addr,
+            &icmp_pkt,
+            &iface_cx.checksum_caps(),
+        )
+        .ok()?;
+
+        match icmp_repr {
+            Icmpv6Repr::Ndisc(ndisc_repr) => Some((ipv6_repr, ndisc_repr)),
+            _ => None,
+        }
+    }
+
+    fn process_ndisc(
+        &self,
+        ipv6_repr: &Ipv6Repr,
+        ndisc_repr: &NdiscRepr,
+        iface_cx: &mut Context,
+    ) -> Option<NeighborMsg> {
+        match ndisc_repr {
+            NdiscRepr::NeighborAdvert {
+                target_addr,
+                lladdr: Some(lladdr),
+                ..
+            } => {
+                let ether_addr = parse_ether_addr(lladdr)?;
+
+                // Ignore the advertisement if the addresses are not unicast.
+                if !ether_addr.is_unicast() || !IpAddress::Ipv6(*target_addr).is_unicast() {
+                    return None;
+                }
+
+                // Insert the mapping between the Ethernet address and the IP address.
+                //
+                // TODO: Remove the mapping if it expires.
+                self.neighbor_table
+                    .lock()
+                    .insert(IpAddress::Ipv6(*target_addr), ether_addr);
+
+                None
+            }
+            NdiscRepr::NeighborSolicit {
+                target_addr,
+                lladdr: Some(lladdr),
+            } => {
+                let ether_addr = parse_ether_addr(lladdr)?;
+
+                // Ignore the solicitation if the source addresses are not unicast. This includes
+                // the solicitations for duplicate address detection, whose source IP address is
+                // unspecified.
+                //
+                // TODO: Reply to the solicitations for duplicate address detection.
+                if !ether_addr.is_unicast() || !IpAddress::Ipv6(ipv6_repr.src_addr).is_unicast() {
+                    return None;
+                }
+
+                // Ignore the solicitation if we do not own the target address.
+                if iface_cx.ipv6_addr().is_none_or(|addr| addr != *target_addr) {
+                    return None;
+                }
+
+                // The solicitation carries the Ethernet address of the sender, so we can learn it
+                // as well. See <https://datatracker.ietf.org/doc/html/rfc4861#section-7.2.3>.
+                self.neighbor_table
+                    .lock()
+                    .insert(IpAddress::Ipv6(ipv6_repr.src_addr), ether_addr);
+
+                let advert = NdiscRepr::NeighborAdvert {
+                    flags: NdiscNeighborFlags::SOLICITED | NdiscNeighborFlags::OVERRIDE,
+                    target_addr: *target_addr,
+                    lladdr: Some(RawHardwareAddress::from_bytes(self.ether_addr.as_bytes())),
+                };
+
+                Some(self.new_ndisc_msg(*target_addr, ipv6_repr.src_addr, ether_addr, advert))
+            }
+            // TODO: Support router discovery and stateless address autoconfiguration.
+            _ => None,
+        }
+    }
+
+    fn new_ndisc_msg(
+        &self,
+        src_addr: Ipv6Address,
+        dst_addr: Ipv6Address,
+        dst_ether_addr: EthernetAddress,
+        ndisc_repr: NdiscRepr<'static>,
+    ) -> NeighborMsg {
+        let ether_repr = EthernetRepr {
+            src_addr: self.ether_addr,
+            dst_addr: dst_ether_addr,
+            ethertype: EthernetProtocol::Ipv6,
+        };
+        let ipv6_repr = Ipv6Repr {
+            src_addr,
+            dst_addr,
+            next_header: IpProtocol::Icmpv6,
+            payload_len: Icmpv6Repr::Ndisc(ndisc_repr).buffer_len(),
+            hop_limit: NDISC_HOP_LIMIT,
+        };
+
+        NeighborMsg::Ndisc(ether_repr, ipv6_repr, ndisc_repr)
+    }
+
+    /// Processes the IPv4 packet if it is a message from a DHCP server.
+    ///
+    /// This method returns `false` if the packet is not a DHCP message or the DHCP client is
+    /// disabled, in which case the packet should be processed as usual.
+    fn process_dhcp(&self, pkt: &Ipv4Packet<&[u8]>, iface_cx: &Context) -> bool {
+        let Some(dhcp_client) = self.dhcp_client.as_ref() else {
+            return false;
+        };
+
+        let checksum_caps = iface_cx.checksum_caps();
+        let parse = || {
+            let ipv4_repr = Ipv4Repr::parse(pkt, &checksum_caps).ok()?;
+            if ipv4_repr.next_header != IpProtocol::Udp {
+                return None;
+            }
+
+            let udp_pkt = UdpPacket::new_checked(pkt.payload()).ok()?;
+            let udp_repr = UdpRepr::parse(
+                &udp_pkt,
+                &IpAddress::Ipv4(ipv4_repr.src_addr),
+                &IpAddress::Ipv4(ipv4_repr.dst_addr),
+                &checksum_caps,
+            )
+            .ok()?;
+            if udp_repr.src_port != DHCP_SERVER_PORT || udp_repr.dst_port != DHCP_CLIENT_PORT {
+                return None;
+            }
+
+            let dhcp_pkt = DhcpPacket::new_checked(udp_pkt.payload()).ok()?;
+            DhcpRepr::parse(&dhcp_pkt).ok()
+        };
+        let Some(dhcp_repr) = parse() else {
+            return false;
+        };
+
+        dhcp_client.lock().process(&dhcp_repr, iface_cx.now());
+
+        true
+    }
+
+    /// Applies the lease changes and sends the DHCP messages that are due.
+    ///
+    /// This method returns the time at which the DHCP client should be polled again, or `None`
+    /// if the DHCP client is disabled.
+    fn poll_dhcp<T: Device + ?Sized>(&self, device: &mut T) -> Option<u64> {
+        let dhcp_client = self.dhcp_client.as_ref()?;
+
+        let now = get_network_timestamp();
+        let (dhcp_repr, lease_update, poll_at) = {
+            let mut dhcp_client = dhcp_client.lock();
+            let dhcp_repr = dhcp_client.poll(now);
+            (
+                dhcp_repr,
+                dhcp_client.take_lease_update(),
+                dhcp_client.poll_at(),
+            )
+        };
+
+        if let Some(lease) = lease_update {
+            self.common.set_dhcp_lease(lease);
+        }
+
+        if let Some(dhcp_repr) = dhcp_repr {
+            let caps = device.capabilities();
+            // If there is no room to send the message, it will be sent again when it is
+            // retransmitted.
+            if let Some(tx_token) = device.transmit(now) {
+                self.emit_dhcp(&dhcp_repr, &caps, tx_token);
+            }
+        }
+
+        Some(poll_at.total_millis() as u64)
+    }
+
+    /// Consumes the token and broadcasts a DHCP message.
+    fn emit_dhcp<T: TxToken>(&self, dhcp_repr: &DhcpRepr, caps: &DeviceCapabilities, tx_token: T) {
+        let mut payload = vec![0; dhcp_repr.buffer_len()];
+        if dhcp_repr
+            .emit(&mut DhcpPacket::new_unchecked(&mut payload[..]))
+            .is_err()
+        {
+            return;
+        }
+
+        let ether_repr = EthernetRepr {
+            src_addr: self.ether_addr,
+            dst_addr: EthernetAddress::BROADCAST,
+            ethertype: EthernetProtocol::Ipv4,
+        };
+        let udp_repr = UdpRepr {
+            src_port: DHCP_CLIENT_PORT,
+            dst_port: DHCP_SERVER_PORT,
+        };
+        let ipv4_repr = Ipv4Repr {
+            src_addr: dhcp_repr.client_ip,
+            dst_addr: Ipv4Address::BROADCAST,
+            next_header: IpProtocol::Udp,
+            payload_len: udp_repr.header_len() + payload.len(),
+            hop_limit: DHCP_HOP_LIMIT,
+        };
+
+        let pkt = Packet::new_ipv4(ipv4_repr, IpPayload::Udp(udp_repr, &payload));
+        self.emit_ip(&ether_repr, &pkt, caps, tx_token);
+    }
+
     fn dispatch<T: TxToken>(&self, pkt: &Packet, iface_cx: &mut Context, tx_token: T) {
-        match self.resolve_ether_or_generate_arp(pkt, iface_cx) {
-            Ok(ether) => Self::emit_ip(&ether, pkt, &iface_cx.caps, tx_token),
-            Err(Some(arp)) => Self::emit_arp(&arp, tx_token),
+        match self.resolve_ether_or_generate_neighbor_msg(pkt, iface_cx) {
+            Ok(ether) => self.emit_ip(&ether, pkt, &iface_cx.caps, tx_token),
+            Err(Some(msg)) => self.emit_neighbor_msg(&msg, &iface_cx.caps, tx_token),
             Err(None) => (),
         }
     }
 
-    fn resolve_ether_or_generate_arp(
+    fn resolve_ether_or_generate_neighbor_msg(
         &self,
         pkt: &Packet,
         iface_cx: &mut Context,
-    ) -> Result<EthernetRepr, Option<ArpRepr>> {
+    ) -> Result<EthernetRepr, Option<NeighborMsg>> {
+        // The IPv4 multicast packets are sent to the multicast Ethernet addresses directly, even
+        // if the route to the multicast group goes through a gateway.
+        if let IpAddress::Ipv4(dst_addr) = pkt.ip_repr().dst_addr() {
+            if dst_addr.is_multicast() {
+                return Ok(EthernetRepr {
+                    src_addr: self.ether_addr,
+                    dst_addr: ipv4_multicast_ether_addr(&dst_addr),
+                    ethertype: EthernetProtocol::Ipv4,
+                });
+            }
+        }
+
         // Resolve the next-hop IP address.
-        let next_hop_ip = match iface_cx.route(&pkt.ip_repr().dst_addr(), iface_cx.now()) {
-            Some(IpAddress::Ipv4(next_hop_ip)) => next_hop_ip,
-            None => return Err(None),
+        let Some(next_hop_ip) = iface_cx.route(&pkt.ip_repr().dst_addr(), iface_cx.now()) else {
+            return Err(None);
         };
 
         // Resolve the next-hop Ethernet address.
-        let next_hop_ether = if next_hop_ip.is_broadcast() {
-            EthernetAddress::BROADCAST
-        } else if let Some(next_hop_ether) = self.arp_table.lock().get(&next_hop_ip) {
-            *next_hop_ether
-        } else {
-            // If the next-hop Ethernet address cannot be resolved, we drop the original packet and
-            // send an ARP packet instead. The upper layer should be responsible for detecting the
-            // packet loss and retrying later to see if the Ethernet address is ready.
-            return Err(Some(ArpRepr::EthernetIpv4 {
-                operation: ArpOperation::Request,
-                source_hardware_addr: self.ether_addr,
-                source_protocol_addr: iface_cx.ipv4_addr().unwrap_or(Ipv4Address::UNSPECIFIED),
-                target_hardware_addr: EthernetAddress::BROADCAST,
-                target_protocol_addr: next_hop_ip,
-            }));
+        let (next_hop_ether, ethertype) = match next_hop_ip {
+            IpAddress::Ipv4(next_hop_ip) => (
+                self.resolve_ipv4_neighbor(next_hop_ip, iface_cx)?,
+                EthernetProtocol::Ipv4,
+            ),
+            IpAddress::Ipv6(next_hop_ip) => (
+                self.resolve_ipv6_neighbor(next_hop_ip, iface_cx)?,
+                EthernetProtocol::Ipv6,
+            ),
         };
 
         Ok(EthernetRepr {
             src_addr: self.ether_addr,
             dst_addr: next_hop_ether,
-            ethertype: EthernetProtocol::Ipv4,
+            ethertype,
         })
     }
 
+    fn resolve_ipv4_neighbor(
+        &self,
+        next_hop_ip: Ipv4Address,
+        iface_cx: &mut Context,
+    ) -> Result<EthernetAddress, Option<NeighborMsg>> {
+        if next_hop_ip.is_broadcast() {
+            return Ok(EthernetAddress::BROADCAST);
+        }
+
+        if let Some(next_hop_ether) = self
+            .neighbor_table
+            .lock()
+            .get(&IpAddress::Ipv4(next_hop_ip))
+        {
+            return Ok(*next_hop_ether);
+        }
+
+        // If the next-hop Ethernet address cannot be resolved, we drop the original packet and
+        // send an ARP packet instead. The upper layer should be responsible for detecting the
+        // packet loss and retrying later to see if the Ethernet address is ready.
+        Err(Some(NeighborMsg::Arp(ArpRepr::EthernetIpv4 {
+            operation: ArpOperation::Request,
+            source_hardware_addr: self.ether_addr,
+            source_protocol_addr: iface_cx.ipv4_addr().unwrap_or(Ipv4Address::UNSPECIFIED),
+            target_hardware_addr: EthernetAddress::BROADCAST,
+            target_protocol_addr: next_hop_ip,
+        })))
+    }
+
+    fn resolve_ipv6_neighbor(
+        &self,
+        next_hop_ip: Ipv6Address,
+        iface_cx: &mut Context,
+    ) -> Result<EthernetAddress, Option<NeighborMsg>> {
+        if next_hop_ip.is_multicast() {
+            return Ok(ipv6_multicast_ether_addr(&next_hop_ip));
+        }
+
+        if let Some(next_hop_ether) = self
+            .neighbor_table
+            .lock()
+            .get(&IpAddress::Ipv6(next_hop_ip))
+        {
+            return Ok(*next_hop_ether);
+        }
+
+        let Some(src_addr) = iface_cx.ipv6_addr() else {
+            return Err(None);
+        };
+
+        // Similar to the IPv4 case, we drop the original packet and send a neighbor solicitation
+        // instead. See <https://datatracker.ietf.org/doc/html/rfc4861#section-7.2.2>.
+        let dst_addr = solicited_node_addr(&next_hop_ip);
+        let solicit = NdiscRepr::NeighborSolicit {
+            target_addr: next_hop_ip,
+            lladdr: Some(RawHardwareAddress::from_bytes(self.ether_addr.as_bytes())),
+        };
+
+        Err(Some(self.new_ndisc_msg(
+            src_addr,
+            dst_addr,
+            ipv6_multicast_ether_addr(&dst_addr),
+            solicit,
+        )))
+    }
+
+    /// Programs the multicast filter of the device if the joined multicast groups have changed.
+    fn update_multicast_filter<T: FilterMulticast + ?Sized>(&self, device: &mut T) {
+        let Some(ipv4_groups) = self.common.take_multicast_filter_update() else {
+            return;
+        };
+
+        let mut ether_addrs: Vec<EthernetAddress> =
+            ipv4_groups.iter().map(ipv4_multicast_ether_addr).collect();
+
+        // IPv6 relies on the all-nodes multicast address and the solicited-node multicast
+        // addresses to discover the neighbors.
+        ether_addrs.push(ipv6_multicast_ether_addr(&IPV6_LINK_LOCAL_ALL_NODES));
+        ether_addrs.extend(self.common.ipv6_cidrs().iter().map(|ipv6_cidr| {
+            ipv6_multicast_ether_addr(&solicited_node_addr(&ipv6_cidr.address()))
+        }));
+
+        // Different IP multicast addresses may be mapped to the same Ethernet address.
+        ether_addrs.sort_unstable();
+        ether_addrs.dedup();
+
+        device.set_multicast_filter(&ether_addrs);
+    }
+
+    /// Consumes the token and emits a neighbor message.
+    fn emit_neighbor_msg<T: TxToken>(
+        &self,
+        msg: &NeighborMsg,
+        caps: &DeviceCapabilities,
+        tx_token: T,
+    ) {
+        match msg {
+            NeighborMsg::Arp(arp_repr) => self.emit_arp(arp_repr, tx_token),
+            NeighborMsg::Ndisc(ether_repr, ipv6_repr, ndisc_repr) => {
+                let pkt = Packet::new_ipv6(
+                    *ipv6_repr,
+                    IpPayload::Icmpv6(Icmpv6Repr::Ndisc(*ndisc_repr)),
+                );
+                self.emit_ip(ether_repr, &pkt, caps, tx_token);
+            }
+        }
+    }
+
     /// Consumes the token and emits an IP packet.
     fn emit_ip<T: TxToken>(
+        &self,
         ether_repr: &EthernetRepr,
         ip_pkt: &Packet,
         caps: &DeviceCapabilities,
@@ -252,12 +692,15 @@ impl<D, E: Ext> EtherIface<D, E> {
                     &mut frame.payload_mut()[ip_repr.header_len()..],
                     caps,
                 );
+
+                self.tap_frame
+                    .tap_frame(frame.into_inner(), FrameDirection::Outgoing);
             },
         );
     }
 
     /// Consumes the token and emits an ARP packet.
-    fn emit_arp<T: TxToken>(arp_repr: &ArpRepr, tx_token: T) {
+    fn emit_arp<T: TxToken>(&self, arp_repr: &ArpRepr, tx_token: T) {
         let ether_repr = match arp_repr {
             ArpRepr::EthernetIpv4 {
                 source_hardware_addr,
@@ -277,6 +720,33 @@ impl<D, E: Ext> EtherIface<D, E> {
 
             let mut pkt = ArpPacket::new_unchecked(frame.payload_mut());
             arp_repr.emit(&mut pkt);
+
+            self.tap_frame
+                .tap_frame(frame.into_inner(), FrameDirection::Outgoing);
         });
     }
 }
+
+/// Parses the link-layer address option of neighbor discovery messages as an Ethernet address.
+fn parse_ether_addr(lladdr: &RawHardwareAddress) -> Option<EthernetAddress> {
+    match lladdr.parse(Medium::Ethernet) {
+        Ok(HardwareAddress::Ethernet(ether_addr)) => Some(ether_addr),
+        _ => None,
+    }
+}
+
+/// Maps the IPv4 multicast address to the Ethernet multicast address.
+///
+/// See <https://datatracker.ietf.org/doc/html/rfc1112#section-6.4>.
+fn ipv4_multicast_ether_addr(addr: &Ipv4Address) -> EthernetAddress {
+    let octets = addr.octets();
+    EthernetAddress([0x01, 0x00, 0x5e, octets[1] & 0x7f, octets[2], octets[3]])
+}
+
+/// Maps the IPv6 multicast address to the Ethernet multicast address.
+///
+/// See <https://datatracker.ietf.org/doc/html/rfc2464#section-7>.
+fn ipv6_multicast_ether_addr(addr: &Ipv6Address) -> EthernetAddress {
+    let octets = addr.octets();
+    EthernetAddress([0x33, 0x33, octets[12], octets[13], octets[14], octets[15]])
+}
@@ -5,15 +5,16 @@ use alloc::{string::String, sync::Arc};
 use smoltcp::{
     iface::Config,
     phy::TxToken,
-    wire::{self, Ipv4Cidr, Ipv4Packet},
+    wire::{self, Ipv4Cidr},
 };
 
 use crate::{
     device::WithDevice,
+    errors::SendFrameError,
     ext::Ext,
     iface::{
-        common::IfaceCommon, iface::internal::IfaceInternal, time::get_network_timestamp, Iface,
-        ScheduleNextPoll,
+        common::IfaceCommon, iface::internal::IfaceInternal, poll::IpPacket,
+        time::get_network_timestamp, Iface, ScheduleNextPoll,
     },
 };
 
@@ -25,23 +26,27 @@ pub struct IpIface<D, E: Ext> {
 impl<D: WithDevice, E: Ext> IpIface<D, E> {
     pub fn new(
         driver: D,
-        ip_cidr: Ipv4Cidr,
+        ip_cidr: Option<Ipv4Cidr>,
         name: String,
         sched_poll: E::ScheduleNextPoll,
+        tap_ip_packet: E::TapIpPacket,
+        filter_ip_packet: E::FilterIpPacket,
     ) -> Arc<Self> {
         let interface = driver.with(|device| {
             let config = Config::new(smoltcp::wire::HardwareAddress::Ip);
             let now = get_network_timestamp();
 
             let mut interface = smoltcp::iface::Interface::new(config, device, now);
-            interface.update_ip_addrs(|ip_addrs| {
-                debug_assert!(ip_addrs.is_empty());
-                ip_addrs.push(wire::IpCidr::Ipv4(ip_cidr)).unwrap();
-            });
+            if let Some(ip_cidr) = ip_cidr {
+                interface.update_ip_addrs(|ip_addrs| {
+                    debug_assert!(ip_addrs.is_empty());
+                    ip_addrs.push(wire::IpCidr::Ipv4(ip_cidr)).unwrap();
+                });
+            }
             interface
         });
 
-        let common = IfaceCommon::new(name, interface, sched_poll);
+        let common = IfaceCommon::new(name, interface, sched_poll, tap_ip_packet, filter_ip_packet);
 
         Arc::new(Self { driver, common })
     }
@@ -58,7 +63,7 @@ impl<D: WithDevice + 'static, E: Ext> Iface<E> for IpIface<D, E> {
         self.driver.with(|device| {
             let next_poll = self.common.poll(
                 device,
-                |data, _iface_cx, tx_token| Some((Ipv4Packet::new_checked(data).ok()?, tx_token)),
+                |data, _iface_cx, tx_token| Some((IpPacket::new_checked(data)?, tx_token)),
                 |pkt, iface_cx, tx_token| {
                     let ip_repr = pkt.ip_repr();
                     tx_token.consume(ip_repr.buffer_len(), |buffer| {
@@ -74,4 +79,8 @@ impl<D: WithDevice + 'static, E: Ext> Iface<E> for IpIface<D, E> {
             self.common.sched_poll().schedule_next_poll(next_poll);
         });
     }
+
+    fn send_frame(&self, _frame: &[u8]) -> Result<(), SendFrameError> {
+        Err(SendFrameError::NotSupported)
+    }
 }
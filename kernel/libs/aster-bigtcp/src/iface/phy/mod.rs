@@ -3,5 +3,5 @@
 mod ether;
 mod ip;
 
-pub use ether::EtherIface;
+pub use ether::{EtherIface, Ipv4Config};
 pub use ip::IpIface;
@@ -1,6 +1,6 @@
 // SPDX-License-Identifier: MPL-2.0
 
-use alloc::{sync::Arc, vec, vec::Vec};
+use alloc::{collections::vec_deque::VecDeque, sync::Arc, vec, vec::Vec};
 
 use smoltcp::{
     iface::{
@@ -9,12 +9,19 @@ use smoltcp::{
     },
     phy::{ChecksumCapabilities, Device, RxToken, TxToken},
     wire::{
-        Icmpv4DstUnreachable, Icmpv4Repr, IpAddress, IpProtocol, IpRepr, Ipv4Address, Ipv4Packet,
-        Ipv4Repr, TcpControl, TcpPacket, TcpRepr, UdpPacket, UdpRepr, IPV4_HEADER_LEN,
-        IPV4_MIN_MTU,
+        Icmpv4DstUnreachable, Icmpv4Packet, Icmpv4Repr, Icmpv6DstUnreachable, Icmpv6Packet,
+        Icmpv6Repr, IpAddress, IpProtocol, IpRepr, IpVersion, Ipv4Address, Ipv4Packet, Ipv4Repr,
+        Ipv6Address, Ipv6Packet, Ipv6Repr, TcpControl, TcpPacket, TcpRepr, UdpPacket, UdpRepr,
+        IPV4_HEADER_LEN, IPV4_MIN_MTU, IPV6_HEADER_LEN, IPV6_MIN_MTU,
     },
 };
 
+use super::{
+    filter::{FilterHook, FilterIpPacket, FilterPacket, FilterVerdict},
+    multicast::{MulticastGroups, IGMP_HOP_LIMIT, IGMP_MSG_LEN},
+    stats::{IfaceStats, SnmpCounter},
+    TapIpPacket,
+};
 use crate::{
     ext::Ext,
     socket::{TcpConnectionBg, TcpProcessResult},
@@ -25,6 +32,10 @@ pub(super) struct PollContext<'a, E: Ext> {
     iface_cx: &'a mut Context,
     sockets: &'a SocketTable<E>,
     new_tcp_conns: &'a mut Vec<Arc<TcpConnectionBg<E>>>,
+    multicast_groups: &'a mut MulticastGroups,
+    tap_ip_packet: &'a E::TapIpPacket,
+    filter_ip_packet: &'a E::FilterIpPacket,
+    stats: &'a IfaceStats,
 }
 
 impl<'a, E: Ext> PollContext<'a, E> {
@@ -32,15 +43,81 @@ impl<'a, E: Ext> PollContext<'a, E> {
         iface_cx: &'a mut Context,
         sockets: &'a SocketTable<E>,
         new_tcp_conns: &'a mut Vec<Arc<TcpConnectionBg<E>>>,
+        multicast_groups: &'a mut MulticastGroups,
+        tap_ip_packet: &'a E::TapIpPacket,
+        filter_ip_packet: &'a E::FilterIpPacket,
+        stats: &'a IfaceStats,
     ) -> Self {
         Self {
             iface_cx,
             sockets,
             new_tcp_conns,
+            multicast_groups,
+            tap_ip_packet,
+            filter_ip_packet,
+            stats,
+        }
+    }
+}
+
+/// An IPv4 or IPv6 packet.
+pub(super) enum IpPacket<T: AsRef<[u8]>> {
+    Ipv4(Ipv4Packet<T>),
+    Ipv6(Ipv6Packet<T>),
+}
+
+impl<T: AsRef<[u8]>> IpPacket<T> {
+    /// Parses the packet according to the IP version in its header.
+    ///
+    /// This method returns `None` if the packet is ill-formed.
+    pub(super) fn new_checked(data: T) -> Option<Self> {
+        match IpVersion::of_packet(data.as_ref()).ok()? {
+            IpVersion::Ipv4 => Ipv4Packet::new_checked(data).ok().map(Self::Ipv4),
+            IpVersion::Ipv6 => Ipv6Packet::new_checked(data).ok().map(Self::Ipv6),
         }
     }
 }
 
+impl<'pkt> IpPacket<&'pkt [u8]> {
+    /// Parses the IP header without verifying the checksum, and returns it along with the
+    /// payload.
+    ///
+    /// This method returns `None` if the header is ill-formed or is not supported.
+    pub(super) fn parse_repr_unchecked(&self) -> Option<(IpRepr, &'pkt [u8])> {
+        let checksum_caps = ChecksumCapabilities::ignored();
+        match self {
+            Self::Ipv4(pkt) => Some((
+                IpRepr::Ipv4(Ipv4Repr::parse(pkt, &checksum_caps).ok()?),
+                pkt.payload(),
+            )),
+            Self::Ipv6(pkt) => Some((IpRepr::Ipv6(Ipv6Repr::parse(pkt).ok()?), pkt.payload())),
+        }
+    }
+}
+
+/// The link-local all-nodes multicast address.
+///
+/// See <https://datatracker.ietf.org/doc/html/rfc4291#section-2.7.1>.
+pub(super) const IPV6_LINK_LOCAL_ALL_NODES: Ipv6Address =
+    Ipv6Address::new(0xff02, 0, 0, 0, 0, 0, 0, 1);
+
+/// Returns the solicited-node multicast address of the IPv6 address.
+///
+/// See <https://datatracker.ietf.org/doc/html/rfc4291#section-2.7.1>.
+pub(super) fn solicited_node_addr(addr: &Ipv6Address) -> Ipv6Address {
+    let octets = addr.octets();
+    Ipv6Address::from([
+        0xff, 0x02, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0x01, 0xff, octets[13], octets[14], octets[15],
+    ])
+}
+
+/// The reason why a destination is unreachable.
+#[derive(Clone, Copy)]
+enum DstUnreachable {
+    Host,
+    Port,
+}
+
 // This works around <https://github.com/rust-lang/rust/issues/49601>.
 // See the issue above for details.
 pub(super) trait FnHelper<A, B, C, O>: FnMut(A, B, C) -> O {}
@@ -58,7 +135,7 @@ impl<E: Ext> PollContext<'_, E> {
             &'pkt [u8],
             &'cx mut Context,
             D::TxToken<'tx>,
-            Option<(Ipv4Packet<&'pkt [u8]>, D::TxToken<'tx>)>,
+            Option<(IpPacket<&'pkt [u8]>, D::TxToken<'tx>)>,
         >,
         Q: FnMut(&Packet, &mut Context, D::TxToken<'_>),
     {
@@ -68,7 +145,11 @@ impl<E: Ext> PollContext<'_, E> {
                     return;
                 };
 
-                let Some(reply) = self.parse_and_process_ipv4(pkt) else {
+                let reply = match pkt {
+                    IpPacket::Ipv4(pkt) => self.parse_and_process_ipv4(pkt),
+                    IpPacket::Ipv6(pkt) => self.parse_and_process_ipv6(pkt),
+                };
+                let Some(reply) = reply else {
                     return;
                 };
 
@@ -81,17 +162,44 @@ impl<E: Ext> PollContext<'_, E> {
         &mut self,
         pkt: Ipv4Packet<&'pkt [u8]>,
     ) -> Option<Packet<'pkt>> {
+        self.stats.inc(SnmpCounter::IpInReceives);
+
         // Parse the IP header. Ignore the packet if the header is ill-formed.
         let repr = Ipv4Repr::parse(&pkt, &self.iface_cx.checksum_caps()).ok()?;
 
-        if !repr.dst_addr.is_broadcast() && !self.is_unicast_local(IpAddress::Ipv4(repr.dst_addr)) {
+        let filter_packet = FilterPacket::parse(&IpRepr::Ipv4(repr), pkt.payload());
+        if !self.is_accepted(FilterHook::Prerouting, &filter_packet) {
+            return None;
+        }
+
+        if repr.dst_addr.is_multicast() {
+            // Ignore the packet if we have not joined the multicast group. Like Linux, also ignore
+            // the packet if it comes from our own address, because it is a packet that we have
+            // sent and that is looped back by the device. Such packets are delivered to the local
+            // sockets when they are sent, if the sockets want them.
+            if !self.multicast_groups.contains(&repr.dst_addr)
+                || self.is_unicast_local(IpAddress::Ipv4(repr.src_addr))
+            {
+                return None;
+            }
+        } else if !repr.dst_addr.is_broadcast()
+            && !self.is_unicast_local(IpAddress::Ipv4(repr.dst_addr))
+        {
             return self.generate_icmp_unreachable(
                 &IpRepr::Ipv4(repr),
                 pkt.payload(),
-                Icmpv4DstUnreachable::HostUnreachable,
+                DstUnreachable::Host,
             );
         }
 
+        if !self.is_accepted(FilterHook::Input, &filter_packet) {
+            return None;
+        }
+        self.stats.inc(SnmpCounter::IpInDelivers);
+
+        self.tap_ip_packet
+            .tap_ip_packet(&pkt.as_ref()[..pkt.total_len() as usize]);
+
         match repr.next_header {
             IpProtocol::Tcp => self.parse_and_process_tcp(
                 &IpRepr::Ipv4(repr),
@@ -103,10 +211,175 @@ impl<E: Ext> PollContext<'_, E> {
                 pkt.payload(),
                 &self.iface_cx.checksum_caps(),
             ),
+            IpProtocol::Icmp => self.parse_and_process_icmpv4(&repr, pkt.payload()),
+            IpProtocol::Igmp => {
+                self.multicast_groups.process_igmp(pkt.payload());
+                None
+            }
             _ => None,
         }
     }
 
+    fn parse_and_process_ipv6<'pkt>(
+        &mut self,
+        pkt: Ipv6Packet<&'pkt [u8]>,
+    ) -> Option<Packet<'pkt>> {
+        self.stats.inc(SnmpCounter::IpInReceives);
+
+        // Parse the IP header. Ignore the packet if the header is ill-formed.
+        let repr = Ipv6Repr::parse(&pkt).ok()?;
+
+        let filter_packet = FilterPacket::parse(&IpRepr::Ipv6(repr), pkt.payload());
+        if !self.is_accepted(FilterHook::Prerouting, &filter_packet) {
+            return None;
+        }
+
+        if repr.dst_addr.is_multicast() {
+            // Ignore the packet if we have not joined the multicast group.
+            //
+            // TODO: Support joining other multicast groups.
+            let is_joined = repr.dst_addr == IPV6_LINK_LOCAL_ALL_NODES
+                || self
+                    .iface_cx
+                    .ipv6_addr()
+                    .is_some_and(|addr| repr.dst_addr == solicited_node_addr(&addr));
+            if !is_joined {
+                return None;
+            }
+        } else if !self.is_unicast_local(IpAddress::Ipv6(repr.dst_addr)) {
+            return self.generate_icmp_unreachable(
+                &IpRepr::Ipv6(repr),
+                pkt.payload(),
+                DstUnreachable::Host,
+            );
+        }
+
+        if !self.is_accepted(FilterHook::Input, &filter_packet) {
+            return None;
+        }
+        self.stats.inc(SnmpCounter::IpInDelivers);
+
+        self.tap_ip_packet
+            .tap_ip_packet(&pkt.as_ref()[..IPV6_HEADER_LEN + pkt.payload_len() as usize]);
+
+        match repr.next_header {
+            IpProtocol::Tcp => self.parse_and_process_tcp(
+                &IpRepr::Ipv6(repr),
+                pkt.payload(),
+                &self.iface_cx.checksum_caps(),
+            ),
+            IpProtocol::Udp => self.parse_and_process_udp(
+                &IpRepr::Ipv6(repr),
+                pkt.payload(),
+                &self.iface_cx.checksum_caps(),
+            ),
+            IpProtocol::Icmpv6 => self.parse_and_process_icmpv6(&repr, pkt.payload()),
+            _ => None,
+        }
+    }
+
+    fn parse_and_process_icmpv4<'pkt>(
+        &mut self,
+        ipv4_repr: &Ipv4Repr,
+        ip_payload: &'pkt [u8],
+    ) -> Option<Packet<'pkt>> {
+        // Parse the ICMP header. Ignore the packet if the header is ill-formed.
+        let icmp_pkt = Icmpv4Packet::new_checked(ip_payload).ok()?;
+        let icmp_repr = Icmpv4Repr::parse(&icmp_pkt, &self.iface_cx.checksum_caps()).ok()?;
+
+        // Other ICMP messages (including echo replies) are delivered to the sockets by
+        // `tap_ip_packet`, so only echo requests need to be answered here.
+        let Icmpv4Repr::EchoRequest {
+            ident,
+            seq_no,
+            data,
+        } = icmp_repr
+        else {
+            return None;
+        };
+
+        // Like Linux, ignore the echo requests sent to broadcast addresses. See
+        // `icmp_echo_ignore_broadcasts` in
+        // <https://www.kernel.org/doc/Documentation/networking/ip-sysctl.txt>.
+        if !IpAddress::Ipv4(ipv4_repr.src_addr).is_unicast() || ipv4_repr.dst_addr.is_broadcast() {
+            return None;
+        }
+
+        let icmp_repr = Icmpv4Repr::EchoReply {
+            ident,
+            seq_no,
+            data,
+        };
+
+        Some(Packet::new_ipv4(
+            Ipv4Repr {
+                src_addr: ipv4_repr.dst_addr,
+                dst_addr: ipv4_repr.src_addr,
+                next_header: IpProtocol::Icmp,
+                payload_len: icmp_repr.buffer_len(),
+                hop_limit: 64,
+            },
+            IpPayload::Icmpv4(icmp_repr),
+        ))
+    }
+
+    fn parse_and_process_icmpv6<'pkt>(
+        &mut self,
+        ipv6_repr: &Ipv6Repr,
+        ip_payload: &'pkt [u8],
+    ) -> Option<Packet<'pkt>> {
+        // Parse the ICMPv6 header. Ignore the packet if the header is ill-formed.
+        let icmp_pkt = Icmpv6Packet::new_checked(ip_payload).ok()?;
+        let icmp_repr = Icmpv6Repr::parse(
+            &ipv6_repr.src_addr,
+            &ipv6_repr.dst_addr,
+            &icmp_pkt,
+            &self.iface_cx.checksum_caps(),
+        )
+        .ok()?;
+
+        // Neighbor discovery messages are handled by the link layer, and other ICMPv6 messages are
+        // delivered to the sockets by `tap_ip_packet`, so only echo requests need to be answered
+        // here.
+        let Icmpv6Repr::EchoRequest {
+            ident,
+            seq_no,
+            data,
+        } = icmp_repr
+        else {
+            return None;
+        };
+
+        if !IpAddress::Ipv6(ipv6_repr.src_addr).is_unicast() {
+            return None;
+        }
+
+        // Reply with the unicast address even if the request is sent to a multicast address. See
+        // <https://datatracker.ietf.org/doc/html/rfc4443#section-4.2>.
+        let src_addr = if ipv6_repr.dst_addr.is_multicast() {
+            self.iface_cx.ipv6_addr()?
+        } else {
+            ipv6_repr.dst_addr
+        };
+
+        let icmp_repr = Icmpv6Repr::EchoReply {
+            ident,
+            seq_no,
+            data,
+        };
+
+        Some(Packet::new_ipv6(
+            Ipv6Repr {
+                src_addr,
+                dst_addr: ipv6_repr.src_addr,
+                next_header: IpProtocol::Icmpv6,
+                payload_len: icmp_repr.buffer_len(),
+                hop_limit: 64,
+            },
+            IpPayload::Icmpv6(icmp_repr),
+        ))
+    }
+
     fn parse_and_process_tcp<'pkt>(
         &mut self,
         ip_repr: &IpRepr,
@@ -129,7 +402,9 @@ impl<E: Ext> PollContext<'_, E> {
             checksum_caps,
         )
         .ok()?;
+        self.stats.inc(SnmpCounter::TcpInSegs);
 
+        // TODO: Count the segments that are looped back below without passing through the device.
         self.process_tcp_until_outgoing(ip_repr, &tcp_repr)
             .map(|(ip_repr, tcp_repr)| Packet::new(ip_repr, IpPayload::Tcp(tcp_repr)))
     }
@@ -146,6 +421,12 @@ impl<E: Ext> PollContext<'_, E> {
                 return Some((ip_repr, tcp_repr));
             }
 
+            let filter_packet =
+                FilterPacket::with_ports(&ip_repr, tcp_repr.src_port, tcp_repr.dst_port);
+            if !self.is_looped_back_accepted(&filter_packet) {
+                return None;
+            }
+
             let (new_ip_repr, new_tcp_repr) = self.process_tcp(&ip_repr, &tcp_repr)?;
             ip_repr = new_ip_repr;
             tcp_repr = new_tcp_repr;
@@ -157,10 +438,17 @@ impl<E: Ext> PollContext<'_, E> {
         ip_repr: &IpRepr,
         tcp_repr: &TcpRepr,
     ) -> Option<(IpRepr, TcpRepr<'static>)> {
+        let connection_key = ConnectionKey::new(
+            ip_repr.dst_addr(),
+            tcp_repr.dst_port,
+            ip_repr.src_addr(),
+            tcp_repr.src_port,
+        );
+
         // Process packets that request to create new connections first.
         if tcp_repr.control == TcpControl::Syn && tcp_repr.ack_number.is_none() {
             let listener_key = ListenerKey::new(ip_repr.dst_addr(), tcp_repr.dst_port);
-            if let Some(listener) = self.sockets.lookup_listener(&listener_key) {
+            if let Some(listener) = self.sockets.lookup_listener(&listener_key, &connection_key) {
                 let (processed, new_tcp_conn) = listener.process(self.iface_cx, ip_repr, tcp_repr);
 
                 if let Some(tcp_conn) = new_tcp_conn {
@@ -178,12 +466,6 @@ impl<E: Ext> PollContext<'_, E> {
         }
 
         // Process packets belonging to existing connections second.
-        let connection_key = ConnectionKey::new(
-            ip_repr.dst_addr(),
-            tcp_repr.dst_port,
-            ip_repr.src_addr(),
-            tcp_repr.src_port,
-        );
         let connection = if let Some(connection) = self.sockets.lookup_connection(&connection_key) {
             Some(connection)
         } else {
@@ -228,12 +510,10 @@ impl<E: Ext> PollContext<'_, E> {
         .ok()?;
 
         if !self.process_udp(ip_repr, &udp_repr, udp_pkt.payload()) {
-            return self.generate_icmp_unreachable(
-                ip_repr,
-                ip_payload,
-                Icmpv4DstUnreachable::PortUnreachable,
-            );
+            self.stats.inc(SnmpCounter::UdpNoPorts);
+            return self.generate_icmp_unreachable(ip_repr, ip_payload, DstUnreachable::Port);
         }
+        self.stats.inc(SnmpCounter::UdpInDatagrams);
 
         None
     }
@@ -259,7 +539,7 @@ impl<E: Ext> PollContext<'_, E> {
         &self,
         ip_repr: &IpRepr,
         ip_payload: &'pkt [u8],
-        reason: Icmpv4DstUnreachable,
+        reason: DstUnreachable,
     ) -> Option<Packet<'pkt>> {
         if !ip_repr.src_addr().is_unicast() || !ip_repr.dst_addr().is_unicast() {
             return None;
@@ -275,28 +555,61 @@ impl<E: Ext> PollContext<'_, E> {
             return None;
         }
 
-        let IpRepr::Ipv4(ipv4_repr) = ip_repr;
+        match ip_repr {
+            IpRepr::Ipv4(ipv4_repr) => {
+                let reason = match reason {
+                    DstUnreachable::Host => Icmpv4DstUnreachable::HostUnreachable,
+                    DstUnreachable::Port => Icmpv4DstUnreachable::PortUnreachable,
+                };
 
-        let reply_len = icmp_reply_payload_len(ip_payload.len(), IPV4_MIN_MTU, IPV4_HEADER_LEN);
-        let icmp_repr = Icmpv4Repr::DstUnreachable {
-            reason,
-            header: *ipv4_repr,
-            data: &ip_payload[..reply_len],
-        };
+                let reply_len =
+                    icmp_reply_payload_len(ip_payload.len(), IPV4_MIN_MTU, IPV4_HEADER_LEN);
+                let icmp_repr = Icmpv4Repr::DstUnreachable {
+                    reason,
+                    header: *ipv4_repr,
+                    data: &ip_payload[..reply_len],
+                };
 
-        Some(Packet::new_ipv4(
-            Ipv4Repr {
-                src_addr: self
-                    .iface_cx
-                    .ipv4_addr()
-                    .unwrap_or(Ipv4Address::UNSPECIFIED),
-                dst_addr: ipv4_repr.src_addr,
-                next_header: IpProtocol::Icmp,
-                payload_len: icmp_repr.buffer_len(),
-                hop_limit: 64,
-            },
-            IpPayload::Icmpv4(icmp_repr),
-        ))
+                Some(Packet::new_ipv4(
+                    Ipv4Repr {
+                        src_addr: self
+                            .iface_cx
+                            .ipv4_addr()
+                            .unwrap_or(Ipv4Address::UNSPECIFIED),
+                        dst_addr: ipv4_repr.src_addr,
+                        next_header: IpProtocol::Icmp,
+                        payload_len: icmp_repr.buffer_len(),
+                        hop_limit: 64,
+                    },
+                    IpPayload::Icmpv4(icmp_repr),
+                ))
+            }
+            IpRepr::Ipv6(ipv6_repr) => {
+                let reason = match reason {
+                    DstUnreachable::Host => Icmpv6DstUnreachable::AddrUnreachable,
+                    DstUnreachable::Port => Icmpv6DstUnreachable::PortUnreachable,
+                };
+
+                let reply_len =
+                    icmp_reply_payload_len(ip_payload.len(), IPV6_MIN_MTU, IPV6_HEADER_LEN);
+                let icmp_repr = Icmpv6Repr::DstUnreachable {
+                    reason,
+                    header: *ipv6_repr,
+                    data: &ip_payload[..reply_len],
+                };
+
+                Some(Packet::new_ipv6(
+                    Ipv6Repr {
+                        src_addr: self.iface_cx.ipv6_addr()?,
+                        dst_addr: ipv6_repr.src_addr,
+                        next_header: IpProtocol::Icmpv6,
+                        payload_len: icmp_repr.buffer_len(),
+                        hop_limit: 64,
+                    },
+                    IpPayload::Icmpv6(icmp_repr),
+                ))
+            }
+        }
     }
 
     /// Returns whether the destination address is the unicast address of a local interface.
@@ -309,24 +622,56 @@ impl<E: Ext> PollContext<'_, E> {
                 .iface_cx
                 .ipv4_addr()
                 .is_some_and(|addr| addr == dst_addr),
+            IpAddress::Ipv6(dst_addr) => self
+                .iface_cx
+                .ipv6_addr()
+                .is_some_and(|addr| addr == dst_addr),
         }
     }
+
+    /// Returns whether the packet filter accepts the packet at the hook.
+    fn is_accepted(&self, hook: FilterHook, packet: &FilterPacket) -> bool {
+        self.filter_ip_packet.filter_ip_packet(hook, packet) == FilterVerdict::Accept
+    }
+
+    /// Returns whether the packet filter accepts the packet that is sent to the local host.
+    ///
+    /// Such a packet passes through the output hook when it is sent, and then passes through the
+    /// prerouting hook and the input hook when it is received.
+    fn is_looped_back_accepted(&self, packet: &FilterPacket) -> bool {
+        [
+            FilterHook::Output,
+            FilterHook::Prerouting,
+            FilterHook::Input,
+        ]
+        .into_iter()
+        .all(|hook| self.is_accepted(hook, packet))
+    }
 }
 
 impl<E: Ext> PollContext<'_, E> {
-    pub(super) fn poll_egress<D, Q>(&mut self, device: &mut D, dispatch_phy: &mut Q)
-    where
+    pub(super) fn poll_egress<D, Q>(
+        &mut self,
+        device: &mut D,
+        dispatch_phy: &mut Q,
+        ip_packets: &mut VecDeque<Vec<u8>>,
+    ) where
         D: Device + ?Sized,
         Q: FnMut(&Packet, &mut Context, D::TxToken<'_>),
     {
         while let Some(tx_token) = device.transmit(self.iface_cx.now()) {
-            if !self.dispatch_ipv4(tx_token, dispatch_phy) {
+            if !self.dispatch_ip(tx_token, dispatch_phy, ip_packets) {
                 break;
             }
         }
     }
 
-    fn dispatch_ipv4<T, Q>(&mut self, tx_token: T, dispatch_phy: &mut Q) -> bool
+    fn dispatch_ip<T, Q>(
+        &mut self,
+        tx_token: T,
+        dispatch_phy: &mut Q,
+        ip_packets: &mut VecDeque<Vec<u8>>,
+    ) -> bool
     where
         T: TxToken,
         Q: FnMut(&Packet, &mut Context, T),
@@ -337,9 +682,22 @@ impl<E: Ext> PollContext<'_, E> {
             return did_something_tcp;
         };
 
-        let (did_something_udp, _tx_token) = self.dispatch_udp(tx_token, dispatch_phy);
+        let (did_something_udp, tx_token) = self.dispatch_udp(tx_token, dispatch_phy);
+
+        let Some(tx_token) = tx_token else {
+            return did_something_tcp || did_something_udp;
+        };
+
+        let (did_something_raw, tx_token) =
+            self.dispatch_ip_packets(tx_token, dispatch_phy, ip_packets);
+
+        let Some(tx_token) = tx_token else {
+            return did_something_tcp || did_something_udp || did_something_raw;
+        };
+
+        let did_something_igmp = self.dispatch_igmp(tx_token, dispatch_phy);
 
-        did_something_tcp || did_something_udp
+        did_something_tcp || did_something_udp || did_something_raw || did_something_igmp
     }
 
     fn dispatch_tcp<T, Q>(&mut self, tx_token: T, dispatch_phy: &mut Q) -> (bool, Option<T>)
@@ -365,7 +723,15 @@ impl<E: Ext> PollContext<'_, E> {
 
             let reply =
                 TcpConnectionBg::dispatch(socket, self.iface_cx, |cx, ip_repr, tcp_repr| {
-                    let mut this = PollContext::new(cx, self.sockets, self.new_tcp_conns);
+                    let mut this = PollContext::new(
+                        cx,
+                        self.sockets,
+                        self.new_tcp_conns,
+                        self.multicast_groups,
+                        self.tap_ip_packet,
+                        self.filter_ip_packet,
+                        self.stats,
+                    );
 
                     if !this.is_unicast_local(ip_repr.dst_addr()) {
                         dispatch_phy(
@@ -376,6 +742,12 @@ impl<E: Ext> PollContext<'_, E> {
                         return None;
                     }
 
+                    let filter_packet =
+                        FilterPacket::with_ports(ip_repr, tcp_repr.src_port, tcp_repr.dst_port);
+                    if !this.is_looped_back_accepted(&filter_packet) {
+                        return None;
+                    }
+
                     if !socket.can_process(tcp_repr.dst_port) {
                         return this.process_tcp(ip_repr, tcp_repr);
                     }
@@ -415,6 +787,12 @@ impl<E: Ext> PollContext<'_, E> {
                     );
                 }
                 (None, Some((ip_repr, tcp_repr))) => {
+                    let filter_packet =
+                        FilterPacket::with_ports(&ip_repr, tcp_repr.src_port, tcp_repr.dst_port);
+                    if !self.is_looped_back_accepted(&filter_packet) {
+                        continue;
+                    }
+
                     if let Some((new_ip_repr, new_tcp_repr)) =
                         self.process_tcp_until_outgoing(&ip_repr, &tcp_repr)
                     {
@@ -456,19 +834,43 @@ impl<E: Ext> PollContext<'_, E> {
             let mut deferred = None;
 
             socket.dispatch(self.iface_cx, |cx, ip_repr, udp_repr, udp_payload| {
-                let mut this = PollContext::new(cx, self.sockets, self.new_tcp_conns);
-
-                if ip_repr.dst_addr().is_broadcast() || !this.is_unicast_local(ip_repr.dst_addr()) {
+                let mut this = PollContext::new(
+                    cx,
+                    self.sockets,
+                    self.new_tcp_conns,
+                    self.multicast_groups,
+                    self.tap_ip_packet,
+                    self.filter_ip_packet,
+                    self.stats,
+                );
+
+                let dst_addr = ip_repr.dst_addr();
+                if dst_addr.is_broadcast() || !this.is_unicast_local(dst_addr) {
                     dispatch_phy(
                         &Packet::new(ip_repr.clone(), IpPayload::Udp(*udp_repr, udp_payload)),
                         this.iface_cx,
                         tx_token.take().unwrap(),
                     );
-                    if !ip_repr.dst_addr().is_broadcast() {
+                    // The broadcast packets are always looped back, but the multicast packets are
+                    // looped back only if the socket wants it and we have joined the group.
+                    let should_loop_back = dst_addr.is_broadcast()
+                        || match dst_addr {
+                            IpAddress::Ipv4(group) if group.is_multicast() => {
+                                socket.is_multicast_loop() && this.multicast_groups.contains(&group)
+                            }
+                            _ => false,
+                        };
+                    if !should_loop_back {
                         return;
                     }
                 }
 
+                let filter_packet =
+                    FilterPacket::with_ports(ip_repr, udp_repr.src_port, udp_repr.dst_port);
+                if !this.is_looped_back_accepted(&filter_packet) {
+                    return;
+                }
+
                 if !socket.can_process(udp_repr.dst_port) {
                     // TODO: Generate the ICMP message here once we're able to handle incoming ICMP
                     // messages.
@@ -509,4 +911,116 @@ impl<E: Ext> PollContext<'_, E> {
 
         (did_something, tx_token)
     }
+
+    fn dispatch_ip_packets<T, Q>(
+        &mut self,
+        tx_token: T,
+        dispatch_phy: &mut Q,
+        ip_packets: &mut VecDeque<Vec<u8>>,
+    ) -> (bool, Option<T>)
+    where
+        T: TxToken,
+        Q: FnMut(&Packet, &mut Context, T),
+    {
+        let mut tx_token = Some(tx_token);
+        let mut did_something = false;
+
+        while let Some(data) = ip_packets.pop_front() {
+            did_something = true;
+
+            // The packets have been checked when they are queued.
+            let Some(pkt) = IpPacket::new_checked(data.as_slice()) else {
+                continue;
+            };
+            let Some((ip_repr, ip_payload)) = pkt.parse_repr_unchecked() else {
+                continue;
+            };
+
+            if !self.is_unicast_local(ip_repr.dst_addr()) {
+                dispatch_phy(
+                    &Packet::new(ip_repr, IpPayload::Raw(ip_payload)),
+                    self.iface_cx,
+                    tx_token.take().unwrap(),
+                );
+                break;
+            }
+
+            // The prerouting hook and the input hook will be checked when the packet is received.
+            if !self.is_accepted(
+                FilterHook::Output,
+                &FilterPacket::parse(&ip_repr, ip_payload),
+            ) {
+                continue;
+            }
+
+            let reply = match pkt {
+                IpPacket::Ipv4(pkt) => self.parse_and_process_ipv4(pkt),
+                IpPacket::Ipv6(pkt) => self.parse_and_process_ipv6(pkt),
+            };
+            let Some(reply) = reply else {
+                continue;
+            };
+
+            if self.is_unicast_local(reply.ip_repr().dst_addr()) {
+                // The reply (e.g., an ICMP echo reply) is destined to the local host again. Only
+                // the sockets that tap the IP packets can be interested in it.
+                self.tap_looped_back(&reply);
+                continue;
+            }
+
+            dispatch_phy(&reply, self.iface_cx, tx_token.take().unwrap());
+            break;
+        }
+
+        (did_something, tx_token)
+    }
+
+    fn dispatch_igmp<T, Q>(&mut self, tx_token: T, dispatch_phy: &mut Q) -> bool
+    where
+        T: TxToken,
+        Q: FnMut(&Packet, &mut Context, T),
+    {
+        // IGMP messages cannot be sent without an IPv4 address.
+        let Some(src_addr) = self.iface_cx.ipv4_addr() else {
+            return false;
+        };
+        let Some(msg) = self.multicast_groups.pop_igmp_msg() else {
+            return false;
+        };
+
+        let data = msg.emit();
+        let ipv4_repr = Ipv4Repr {
+            src_addr,
+            dst_addr: msg.dst_addr(),
+            next_header: IpProtocol::Igmp,
+            payload_len: IGMP_MSG_LEN,
+            hop_limit: IGMP_HOP_LIMIT,
+        };
+        dispatch_phy(
+            &Packet::new_ipv4(ipv4_repr, IpPayload::Raw(&data)),
+            self.iface_cx,
+            tx_token,
+        );
+
+        true
+    }
+
+    /// Taps a packet that is sent to the local host as if it is received by the iface.
+    fn tap_looped_back(&self, pkt: &Packet) {
+        if !self.is_looped_back_accepted(&FilterPacket::of_packet(pkt)) {
+            return;
+        }
+
+        let ip_repr = pkt.ip_repr();
+
+        let mut data = vec![0; ip_repr.buffer_len()];
+        ip_repr.emit(&mut data[..], &self.iface_cx.checksum_caps());
+        pkt.emit_payload(
+            &ip_repr,
+            &mut data[ip_repr.header_len()..],
+            &self.iface_cx.caps,
+        );
+
+        self.tap_ip_packet.tap_ip_packet(&data);
+    }
 }
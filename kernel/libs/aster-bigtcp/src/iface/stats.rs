@@ -0,0 +1,78 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! The statistics of ifaces.
+//!
+//! The counters are a subset of the SNMP MIB counters that Linux reports in `/proc/net/snmp`.
+
+use core::sync::atomic::{AtomicU64, Ordering};
+
+use smoltcp::{
+    iface::packet::{IpPayload, Packet},
+    wire::TcpControl,
+};
+
+/// A counter of the packets that pass through an iface.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SnmpCounter {
+    /// The IP packets received from the device, including the ones with errors.
+    IpInReceives,
+    /// The IP packets delivered to the local host.
+    IpInDelivers,
+    /// The IP packets sent to the device.
+    IpOutRequests,
+    /// The TCP connections initiated by the local host.
+    TcpActiveOpens,
+    /// The TCP connections accepted by the local listeners.
+    TcpPassiveOpens,
+    /// The TCP segments received.
+    TcpInSegs,
+    /// The TCP segments sent.
+    TcpOutSegs,
+    /// The TCP segments sent with the RST flag.
+    TcpOutRsts,
+    /// The UDP datagrams delivered to the local sockets.
+    UdpInDatagrams,
+    /// The UDP datagrams received without any local sockets on the destination port.
+    UdpNoPorts,
+    /// The UDP datagrams sent.
+    UdpOutDatagrams,
+}
+
+const NUM_SNMP_COUNTERS: usize = SnmpCounter::UdpOutDatagrams as usize + 1;
+
+/// The counters of an iface.
+pub(crate) struct IfaceStats {
+    counters: [AtomicU64; NUM_SNMP_COUNTERS],
+}
+
+impl IfaceStats {
+    pub(crate) const fn new() -> Self {
+        Self {
+            counters: [const { AtomicU64::new(0) }; NUM_SNMP_COUNTERS],
+        }
+    }
+
+    pub(crate) fn inc(&self, counter: SnmpCounter) {
+        self.counters[counter as usize].fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub(crate) fn get(&self, counter: SnmpCounter) -> u64 {
+        self.counters[counter as usize].load(Ordering::Relaxed)
+    }
+
+    /// Counts a packet that is sent to the device.
+    pub(super) fn count_outgoing(&self, pkt: &Packet) {
+        self.inc(SnmpCounter::IpOutRequests);
+
+        match pkt.payload() {
+            IpPayload::Tcp(tcp_repr) => {
+                self.inc(SnmpCounter::TcpOutSegs);
+                if tcp_repr.control == TcpControl::Rst {
+                    self.inc(SnmpCounter::TcpOutRsts);
+                }
+            }
+            IpPayload::Udp(..) => self.inc(SnmpCounter::UdpOutDatagrams),
+            _ => (),
+        }
+    }
+}
@@ -0,0 +1,31 @@
+// SPDX-License-Identifier: MPL-2.0
+
+/// The direction of a link-layer frame.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FrameDirection {
+    /// The frame is received from the device.
+    Incoming,
+    /// The frame is sent to the device.
+    Outgoing,
+}
+
+/// A trait to provide the `tap_frame` method for ifaces.
+pub trait TapFrame: Send + Sync {
+    /// Taps a link-layer frame.
+    ///
+    /// This is invoked with every frame that the iface receives from or sends to the device,
+    /// including the received frames that are ignored by the iface. The implementation must not
+    /// poll the iface, since the iface is being polled when this method is invoked.
+    fn tap_frame(&self, frame: &[u8], direction: FrameDirection);
+}
+
+/// A trait to provide the `tap_ip_packet` method for ifaces.
+pub trait TapIpPacket: Send + Sync {
+    /// Taps an IP packet.
+    ///
+    /// This is invoked with every IPv4 or IPv6 packet that the iface accepts for the local host,
+    /// before the packet is handled by the transport layer. The packet starts with the IP header
+    /// and does not contain any trailing bytes. The implementation must not poll the iface, since
+    /// the iface is being polled when this method is invoked.
+    fn tap_ip_packet(&self, packet: &[u8]);
+}
@@ -20,7 +20,8 @@ use super::{
     event::{SocketEventObserver, SocketEvents},
     option::{RawTcpOption, RawTcpSetOption},
     unbound::{new_tcp_socket, new_udp_socket},
-    RawTcpSocket, RawUdpSocket, TcpStateCheck,
+    RawTcpCongestionControl, RawTcpSocket, RawUdpSocket, TcpSocketInfo, TcpStateCheck,
+    UdpSocketInfo,
 };
 use crate::{
     errors::{
@@ -28,7 +29,7 @@ use crate::{
         udp::SendError,
     },
     ext::Ext,
-    iface::{BindPortConfig, BoundPort, Iface},
+    iface::{BindPortConfig, BoundPort, Iface, SnmpCounter},
     socket_table::{ConnectionKey, ListenerKey},
 };
 
@@ -214,13 +215,16 @@ pub struct TcpBacklog<E: Ext> {
 pub struct TcpListenerInner<E: Ext> {
     backlog: SpinLock<TcpBacklog<E>, LocalIrqDisabled>,
     listener_key: ListenerKey,
+    /// Whether the listener is in an `SO_REUSEPORT` group.
+    reuse_port: bool,
 }
 
 impl<E: Ext> TcpListenerInner<E> {
-    fn new(backlog: TcpBacklog<E>, listener_key: ListenerKey) -> Self {
+    fn new(backlog: TcpBacklog<E>, listener_key: ListenerKey, reuse_port: bool) -> Self {
         Self {
             backlog: SpinLock::new(backlog),
             listener_key,
+            reuse_port,
         }
     }
 }
@@ -250,7 +254,32 @@ impl<E: Ext> Inner<E> for TcpListenerInner<E> {
 }
 
 /// States needed by [`UdpSocketBg`].
-type UdpSocketInner = SpinLock<Box<RawUdpSocket>, LocalIrqDisabled>;
+pub struct UdpSocketInner {
+    socket: SpinLock<Box<RawUdpSocket>, LocalIrqDisabled>,
+    /// The hop limit of the outgoing multicast packets.
+    multicast_hop_limit: AtomicU8,
+    /// Whether the outgoing multicast packets are looped back to the local sockets.
+    is_multicast_loop: AtomicBool,
+}
+
+/// The default hop limit of the outgoing multicast packets.
+///
+/// Like Linux, the multicast packets are not forwarded beyond the local network by default.
+const DEFAULT_MULTICAST_HOP_LIMIT: u8 = 1;
+
+impl UdpSocketInner {
+    fn new(socket: Box<RawUdpSocket>) -> Self {
+        Self {
+            socket: SpinLock::new(socket),
+            multicast_hop_limit: AtomicU8::new(DEFAULT_MULTICAST_HOP_LIMIT),
+            is_multicast_loop: AtomicBool::new(true),
+        }
+    }
+
+    fn lock(&self) -> SpinLockGuard<Box<RawUdpSocket>, LocalIrqDisabled> {
+        self.socket.lock()
+    }
+}
 
 impl<E: Ext> Inner<E> for UdpSocketInner {
     type Observer = E::UdpEventObserver;
@@ -380,6 +409,8 @@ impl<E: Ext> TcpConnection<E> {
         let res = sockets.insert_connection(connection.inner().clone());
         debug_assert!(res.is_ok());
 
+        iface.common().stats().inc(SnmpCounter::TcpActiveOpens);
+
         Ok(connection)
     }
 
@@ -467,6 +498,17 @@ impl<E: Ext> TcpConnection<E> {
         self.0.update_next_poll_at_ms(PollAt::Now);
     }
 
+    /// Aborts the connection, sending a RST to the peer if needed.
+    ///
+    /// Polling the iface is _always_ required after this method succeeds.
+    pub fn abort(&self) {
+        let mut socket = self.0.inner.lock();
+
+        socket.listener = None;
+        socket.abort();
+        self.0.update_next_poll_at_ms(PollAt::Now);
+    }
+
     /// Calls `f` with an immutable reference to the associated [`RawTcpSocket`].
     //
     // NOTE: If a mutable reference is required, add a method above that correctly updates the next
@@ -493,19 +535,41 @@ impl<E: Ext> RawTcpSetOption for TcpConnection<E> {
         }
     }
 
+    fn set_timeout(&self, timeout: Option<Duration>) -> NeedIfacePoll {
+        let mut socket = self.0.inner.lock();
+        socket.set_timeout(timeout);
+
+        if timeout.is_some() {
+            self.0.update_next_poll_at_ms(PollAt::Now);
+            NeedIfacePoll::TRUE
+        } else {
+            NeedIfacePoll::FALSE
+        }
+    }
+
     fn set_nagle_enabled(&self, enabled: bool) {
         let mut socket = self.0.inner.lock();
         socket.set_nagle_enabled(enabled);
     }
+
+    fn set_congestion_control(&self, congestion_control: RawTcpCongestionControl) {
+        let mut socket = self.0.inner.lock();
+        socket.set_congestion_control(congestion_control);
+    }
 }
 
 impl<E: Ext> TcpListener<E> {
     /// Listens at a specified endpoint.
     ///
+    /// If `reuse_port` is true, the listener can share the endpoint with other listeners whose
+    /// `reuse_port` is also true. The new connections to the endpoint are then distributed among
+    /// these listeners according to the hash of their remote endpoints.
+    ///
     /// Polling the iface is _not_ required after this method succeeds.
     pub fn new_listen(
         bound: BoundPort<E>,
         max_conn: usize,
+        reuse_port: bool,
         option: &RawTcpOption,
         observer: E::TcpEventObserver,
     ) -> Result<Self, (BoundPort<E>, ListenError)> {
@@ -518,7 +582,7 @@ impl<E: Ext> TcpListener<E> {
 
         let listener_key = ListenerKey::new(local_endpoint.addr, local_endpoint.port);
 
-        if sockets.lookup_listener(&listener_key).is_some() {
+        if !sockets.can_insert_listener(&listener_key, reuse_port) {
             return Err((bound, ListenError::AddressInUse));
         }
 
@@ -542,7 +606,7 @@ impl<E: Ext> TcpListener<E> {
                 connected: Vec::new(),
             };
 
-            TcpListenerInner::new(backlog, listener_key)
+            TcpListenerInner::new(backlog, listener_key, reuse_port)
         };
 
         let listener = Self::new(bound, inner);
@@ -590,10 +654,22 @@ impl<E: Ext> RawTcpSetOption for TcpListener<E> {
         NeedIfacePoll::FALSE
     }
 
+    fn set_timeout(&self, timeout: Option<Duration>) -> NeedIfacePoll {
+        let mut backlog = self.0.inner.backlog.lock();
+        backlog.socket.set_timeout(timeout);
+
+        NeedIfacePoll::FALSE
+    }
+
     fn set_nagle_enabled(&self, enabled: bool) {
         let mut backlog = self.0.inner.backlog.lock();
         backlog.socket.set_nagle_enabled(enabled);
     }
+
+    fn set_congestion_control(&self, congestion_control: RawTcpCongestionControl) {
+        let mut backlog = self.0.inner.backlog.lock();
+        backlog.socket.set_congestion_control(congestion_control);
+    }
 }
 
 impl<E: Ext> UdpSocket<E> {
@@ -673,6 +749,27 @@ impl<E: Ext> UdpSocket<E> {
         Ok(result)
     }
 
+    /// Sets the hop limit of the outgoing multicast packets.
+    ///
+    /// Polling the iface is _not_ required after this method succeeds.
+    pub fn set_multicast_hop_limit(&self, hop_limit: u8) {
+        self.0
+            .inner
+            .multicast_hop_limit
+            .store(hop_limit, Ordering::Relaxed);
+    }
+
+    /// Sets whether the outgoing multicast packets are looped back to the local sockets that
+    /// have joined the multicast group.
+    ///
+    /// Polling the iface is _not_ required after this method succeeds.
+    pub fn set_multicast_loop(&self, is_loop: bool) {
+        self.0
+            .inner
+            .is_multicast_loop
+            .store(is_loop, Ordering::Relaxed);
+    }
+
     /// Calls `f` with an immutable reference to the associated [`RawUdpSocket`].
     //
     // NOTE: If a mutable reference is required, add a method above that correctly updates the next
@@ -778,12 +875,62 @@ impl<E: Ext> TcpConnectionBg<E> {
     pub(crate) const fn connection_key(&self) -> &ConnectionKey {
         &self.inner.connection_key
     }
+
+    /// Returns the information of the TCP connection, or `None` if it has been closed.
+    pub(crate) fn info(&self) -> Option<TcpSocketInfo> {
+        let socket = self.inner.lock();
+
+        Some(TcpSocketInfo {
+            local_endpoint: socket.local_endpoint()?,
+            remote_endpoint: Some(socket.remote_endpoint()?),
+            state: socket.state(),
+            send_queue: socket.send_queue(),
+            recv_queue: socket.recv_queue(),
+        })
+    }
 }
 
 impl<E: Ext> TcpListenerBg<E> {
     pub(crate) const fn listener_key(&self) -> &ListenerKey {
         &self.inner.listener_key
     }
+
+    pub(crate) const fn reuse_port(&self) -> bool {
+        self.inner.reuse_port
+    }
+
+    /// Returns the information of the TCP listener.
+    pub(crate) fn info(&self) -> Option<TcpSocketInfo> {
+        let backlog = self.inner.backlog.lock();
+        let listen_endpoint = backlog.socket.listen_endpoint();
+
+        Some(TcpSocketInfo {
+            local_endpoint: IpEndpoint::new(listen_endpoint.addr?, listen_endpoint.port),
+            remote_endpoint: None,
+            state: backlog.socket.state(),
+            send_queue: backlog.max_conn,
+            recv_queue: backlog.connected.len(),
+        })
+    }
+}
+
+impl<E: Ext> UdpSocketBg<E> {
+    /// Returns the information of the UDP socket.
+    pub(crate) fn info(&self) -> Option<UdpSocketInfo> {
+        let socket = self.inner.lock();
+        let endpoint = socket.endpoint();
+
+        let local_endpoint = match endpoint.addr {
+            Some(addr) => IpEndpoint::new(addr, endpoint.port),
+            None => self.bound.endpoint()?,
+        };
+
+        Some(UdpSocketInfo {
+            local_endpoint,
+            send_queue: socket.send_queue(),
+            recv_queue: socket.recv_queue(),
+        })
+    }
 }
 
 impl<T: Inner<E>, E: Ext> SocketBg<T, E> {
@@ -951,7 +1098,10 @@ impl<E: Ext> TcpListenerBg<E> {
         let conn = TcpConnection::new(
             self.bound
                 .iface()
-                .bind(BindPortConfig::CanReuse(self.bound.port()))
+                .bind(
+                    self.bound.ip_version(),
+                    BindPortConfig::CanReuse(self.bound.port()),
+                )
                 .unwrap(),
             inner,
         );
@@ -995,6 +1145,11 @@ impl<E: Ext> UdpSocketBg<E> {
         true
     }
 
+    /// Returns whether the outgoing multicast packets should be looped back.
+    pub(crate) fn is_multicast_loop(&self) -> bool {
+        self.inner.is_multicast_loop.load(Ordering::Relaxed)
+    }
+
     /// Tries to generate an outgoing packet and dispatches the generated packet.
     pub(crate) fn dispatch<D>(&self, cx: &mut Context, dispatch: D)
     where
@@ -1003,7 +1158,14 @@ impl<E: Ext> UdpSocketBg<E> {
         let mut socket = self.inner.lock();
 
         socket
-            .dispatch(cx, |cx, _meta, (ip_repr, udp_repr, udp_payload)| {
+            .dispatch(cx, |cx, _meta, (mut ip_repr, udp_repr, udp_payload)| {
+                if ip_repr.dst_addr().is_multicast() {
+                    let hop_limit = self.inner.multicast_hop_limit.load(Ordering::Relaxed);
+                    match &mut ip_repr {
+                        IpRepr::Ipv4(ipv4_repr) => ipv4_repr.hop_limit = hop_limit,
+                        IpRepr::Ipv6(ipv6_repr) => ipv6_repr.hop_limit = hop_limit,
+                    }
+                }
                 dispatch(cx, &ip_repr, &udp_repr, udp_payload);
                 Ok::<(), ()>(())
             })
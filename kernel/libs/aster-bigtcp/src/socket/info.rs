@@ -0,0 +1,30 @@
+// SPDX-License-Identifier: MPL-2.0
+
+use smoltcp::wire::IpEndpoint;
+
+use super::TcpState;
+
+/// The information of a TCP socket, which is a TCP connection or a TCP listener.
+#[derive(Debug, Clone, Copy)]
+pub struct TcpSocketInfo {
+    pub local_endpoint: IpEndpoint,
+    /// The remote endpoint, or `None` if the socket is a TCP listener.
+    pub remote_endpoint: Option<IpEndpoint>,
+    pub state: TcpState,
+    /// The number of bytes waiting to be sent, or the maximum number of pending connections if
+    /// the socket is a TCP listener.
+    pub send_queue: usize,
+    /// The number of bytes waiting to be received, or the number of pending connections if the
+    /// socket is a TCP listener.
+    pub recv_queue: usize,
+}
+
+/// The information of a UDP socket.
+#[derive(Debug, Clone, Copy)]
+pub struct UdpSocketInfo {
+    pub local_endpoint: IpEndpoint,
+    /// The number of bytes waiting to be sent.
+    pub send_queue: usize,
+    /// The number of bytes waiting to be received.
+    pub recv_queue: usize,
+}
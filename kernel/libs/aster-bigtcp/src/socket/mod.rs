@@ -2,6 +2,7 @@
 
 mod bound;
 mod event;
+mod info;
 mod option;
 mod state;
 mod unbound;
@@ -9,9 +10,11 @@ mod unbound;
 pub use bound::{ConnectState, NeedIfacePoll, TcpConnection, TcpListener, UdpSocket};
 pub(crate) use bound::{TcpConnectionBg, TcpListenerBg, TcpProcessResult, UdpSocketBg};
 pub use event::{SocketEventObserver, SocketEvents};
+pub use info::{TcpSocketInfo, UdpSocketInfo};
 pub use option::{RawTcpOption, RawTcpSetOption};
-pub use state::TcpStateCheck;
+pub use state::{TcpState, TcpStateCheck};
 pub use unbound::{TCP_RECV_BUF_LEN, TCP_SEND_BUF_LEN, UDP_RECV_PAYLOAD_LEN, UDP_SEND_PAYLOAD_LEN};
 
 pub type RawTcpSocket = smoltcp::socket::tcp::Socket<'static>;
 pub type RawUdpSocket = smoltcp::socket::udp::Socket<'static>;
+pub type RawTcpCongestionControl = smoltcp::socket::tcp::CongestionControl;
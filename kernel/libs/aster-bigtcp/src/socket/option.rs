@@ -2,7 +2,7 @@
 
 use smoltcp::time::Duration;
 
-use super::{NeedIfacePoll, RawTcpSocket};
+use super::{NeedIfacePoll, RawTcpCongestionControl, RawTcpSocket};
 
 /// A trait defines setting socket options on a raw socket.
 pub trait RawTcpSetOption {
@@ -11,28 +11,47 @@ pub trait RawTcpSetOption {
     /// Polling the iface _may_ be required after this method succeeds.
     fn set_keep_alive(&self, interval: Option<Duration>) -> NeedIfacePoll;
 
+    /// Sets the timeout, after which the connection will be aborted if nothing is received from
+    /// the peer.
+    ///
+    /// Polling the iface _may_ be required after this method succeeds.
+    fn set_timeout(&self, timeout: Option<Duration>) -> NeedIfacePoll;
+
     /// Enables or disables Nagle’s Algorithm.
     ///
     /// Polling the iface is _not_ required after this method succeeds.
     fn set_nagle_enabled(&self, enabled: bool);
+
+    /// Sets the congestion control algorithm.
+    ///
+    /// Polling the iface is _not_ required after this method succeeds.
+    fn set_congestion_control(&self, congestion_control: RawTcpCongestionControl);
 }
 
 /// Socket options on a raw socket.
 pub struct RawTcpOption {
     /// The keep alive interval.
     pub keep_alive: Option<Duration>,
+    /// The timeout after which the connection is aborted if nothing is received from the peer.
+    pub timeout: Option<Duration>,
     /// Whether Nagle's algorithm is enabled.
     pub is_nagle_enabled: bool,
+    /// The congestion control algorithm.
+    pub congestion_control: RawTcpCongestionControl,
 }
 
 impl RawTcpOption {
     pub(super) fn apply(&self, socket: &mut RawTcpSocket) {
         socket.set_keep_alive(self.keep_alive);
+        socket.set_timeout(self.timeout);
         socket.set_nagle_enabled(self.is_nagle_enabled);
+        socket.set_congestion_control(self.congestion_control);
     }
 
     pub(super) fn inherit(from: &RawTcpSocket, to: &mut RawTcpSocket) {
         to.set_keep_alive(from.keep_alive());
+        to.set_timeout(from.timeout());
         to.set_nagle_enabled(from.nagle_enabled());
+        to.set_congestion_control(from.congestion_control());
     }
 }
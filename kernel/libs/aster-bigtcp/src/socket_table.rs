@@ -18,11 +18,12 @@ use crate::{
 
 pub type SocketHash = u32;
 
-/// A unique key for identifying a `TcpListener`.
+/// A key for identifying a `TcpListener`.
 ///
 /// Note that two `TcpListener`s cannot listen on the same address
 /// even if both sockets set SO_REUSEADDR to true,
-/// so there cannot be multiple listeners with the same `ListenerKey`.
+/// so there can be multiple listeners with the same `ListenerKey`
+/// only if all of them set SO_REUSEPORT to true.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub(crate) struct ListenerKey {
     addr: IpAddress,
@@ -103,23 +104,29 @@ const fn hash_local_remote(
     remote_addr: IpAddress,
     remote_port: PortNum,
 ) -> SocketHash {
-    // FIXME: Deal with IPv6 addresses once IPv6 is supported.
-    let IpAddress::Ipv4(local_ipv4) = local_addr;
-    let IpAddress::Ipv4(remote_ipv4) = remote_addr;
-
     jhash_3vals(
-        local_ipv4.to_bits(),
-        remote_ipv4.to_bits(),
+        fold_addr(local_addr),
+        fold_addr(remote_addr),
         (local_port as u32).wrapping_shl(16) | remote_port as u32,
         HASH_SECRET.wrapping_add(NET_HASHMIX),
     )
 }
 
 const fn hash_addr_port(addr: IpAddress, port: PortNum) -> SocketHash {
-    // FIXME: Deal with IPv6 addresses once IPv6 is supported.
-    let IpAddress::Ipv4(ipv4_addr) = addr;
+    jhash_1vals(fold_addr(addr), NET_HASHMIX) ^ (port as u32)
+}
 
-    jhash_1vals(ipv4_addr.to_bits(), NET_HASHMIX) ^ (port as u32)
+/// Folds the IP address into a 32-bit value for hashing.
+///
+/// IPv6 addresses are folded by XORing their four 32-bit words.
+const fn fold_addr(addr: IpAddress) -> u32 {
+    match addr {
+        IpAddress::Ipv4(ipv4_addr) => ipv4_addr.to_bits(),
+        IpAddress::Ipv6(ipv6_addr) => {
+            let bits = ipv6_addr.to_bits();
+            (bits ^ (bits >> 32) ^ (bits >> 64) ^ (bits >> 96)) as u32
+        }
+    }
 }
 
 /// The socket table manages TCP and UDP sockets.
@@ -176,9 +183,27 @@ impl<E: Ext> SocketTable<E> {
         }
     }
 
+    /// Returns whether a TCP listener with the [`ListenerKey`] can be inserted into the table.
+    ///
+    /// A listener can be inserted if no listener with the same key has been inserted, or if
+    /// both the new listener and all the existing ones set SO_REUSEPORT to true.
+    pub(crate) fn can_insert_listener(&self, key: &ListenerKey, reuse_port: bool) -> bool {
+        let bucket = {
+            let hash = key.hash();
+            let bucket_index = hash & LISTENER_BUCKET_MASK;
+            &self.listener_buckets[bucket_index as usize]
+        };
+
+        bucket
+            .listeners
+            .iter()
+            .filter(|tcp_listener| tcp_listener.listener_key() == key)
+            .all(|tcp_listener| reuse_port && tcp_listener.reuse_port())
+    }
+
     /// Inserts a TCP listener into the table.
     ///
-    /// If a socket with the same [`ListenerKey`] has already been inserted,
+    /// If the listener cannot be inserted (see [`Self::can_insert_listener`]),
     /// this method will return an error and the listener will not be inserted.
     pub(crate) fn insert_listener(
         &mut self,
@@ -186,20 +211,16 @@ impl<E: Ext> SocketTable<E> {
     ) -> Result<(), Arc<TcpListenerBg<E>>> {
         let key = listener.listener_key();
 
+        if !self.can_insert_listener(key, listener.reuse_port()) {
+            return Err(listener);
+        }
+
         let bucket = {
             let hash = key.hash();
             let bucket_index = hash & LISTENER_BUCKET_MASK;
             &mut self.listener_buckets[bucket_index as usize]
         };
 
-        if bucket
-            .listeners
-            .iter()
-            .any(|tcp_listener| tcp_listener.listener_key() == listener.listener_key())
-        {
-            return Err(listener);
-        }
-
         bucket.listeners.push(listener);
         Ok(())
     }
@@ -236,17 +257,35 @@ impl<E: Ext> SocketTable<E> {
         self.udp_sockets.push(udp_socket);
     }
 
-    pub(crate) fn lookup_listener(&self, key: &ListenerKey) -> Option<&Arc<TcpListenerBg<E>>> {
+    /// Looks up the TCP listener that should accept a new connection.
+    ///
+    /// If multiple listeners in an SO_REUSEPORT group have the [`ListenerKey`], one of them is
+    /// selected according to the hash of the [`ConnectionKey`] of the new connection, so that the
+    /// new connections are distributed among the listeners.
+    pub(crate) fn lookup_listener(
+        &self,
+        key: &ListenerKey,
+        connection_key: &ConnectionKey,
+    ) -> Option<&Arc<TcpListenerBg<E>>> {
         let bucket = {
             let hash = key.hash();
             let bucket_index = hash & LISTENER_BUCKET_MASK;
             &self.listener_buckets[bucket_index as usize]
         };
 
-        bucket
+        let mut listeners = bucket
             .listeners
             .iter()
-            .find(|listener| listener.listener_key() == key)
+            .filter(|listener| listener.listener_key() == key);
+
+        let num_listeners = listeners.clone().count();
+        if num_listeners <= 1 {
+            return listeners.next();
+        }
+
+        // This follows `reciprocal_scale` in Linux, which maps the hash to `0..num_listeners`.
+        let index = ((connection_key.hash() as u64 * num_listeners as u64) >> 32) as usize;
+        listeners.nth(index)
     }
 
     pub(crate) fn lookup_connection(
@@ -280,7 +319,7 @@ impl<E: Ext> SocketTable<E> {
         let index = bucket
             .listeners
             .iter()
-            .position(|tcp_listener| tcp_listener.listener_key() == listener.listener_key())?;
+            .position(|tcp_listener| core::ptr::eq(tcp_listener.as_ref(), listener))?;
         Some(bucket.listeners.swap_remove(index))
     }
 
@@ -0,0 +1,187 @@
+// SPDX-License-Identifier: MPL-2.0
+
+use ostd::{
+    cpu::{CpuException, CpuExceptionInfo, RawGeneralRegs, UserContext},
+    Pod,
+};
+
+use crate::{
+    cpu::LinuxAbi, process::posix_thread::seccomp::AUDIT_ARCH, thread::exception::PageFaultInfo,
+    vm::perms::VmPerms,
+};
+
+impl LinuxAbi for UserContext {
+    fn syscall_num(&self) -> usize {
+        self.x8()
+    }
+
+    fn syscall_ret(&self) -> usize {
+        self.x0()
+    }
+
+    fn set_syscall_ret(&mut self, ret: usize) {
+        self.set_x0(ret)
+    }
+
+    fn syscall_args(&self) -> [usize; 6] {
+        [
+            self.x0(),
+            self.x1(),
+            self.x2(),
+            self.x3(),
+            self.x4(),
+            self.x5(),
+        ]
+    }
+
+    fn set_tls_pointer(&mut self, tls: usize) {
+        self.set_tpidr(tls);
+    }
+
+    fn tls_pointer(&self) -> usize {
+        self.tpidr()
+    }
+    fn frame_pointer(&self) -> usize {
+        self.x29()
+    }
+
+    fn syscall_arch(&self) -> u32 {
+        AUDIT_ARCH
+    }
+}
+
+/// General-purpose registers.
+#[derive(Debug, Clone, Copy, Pod, Default)]
+#[repr(C)]
+pub struct GpRegs {
+    pub x0: usize,
+    pub x1: usize,
+    pub x2: usize,
+    pub x3: usize,
+    pub x4: usize,
+    pub x5: usize,
+    pub x6: usize,
+    pub x7: usize,
+    pub x8: usize,
+    pub x9: usize,
+    pub x10: usize,
+    pub x11: usize,
+    pub x12: usize,
+    pub x13: usize,
+    pub x14: usize,
+    pub x15: usize,
+    pub x16: usize,
+    pub x17: usize,
+    pub x18: usize,
+    pub x19: usize,
+    pub x20: usize,
+    pub x21: usize,
+    pub x22: usize,
+    pub x23: usize,
+    pub x24: usize,
+    pub x25: usize,
+    pub x26: usize,
+    pub x27: usize,
+    pub x28: usize,
+    pub x29: usize,
+    pub x30: usize,
+    pub sp: usize,
+}
+
+macro_rules! copy_gp_regs {
+    ($src: ident, $dst: ident) => {
+        $dst.x0 = $src.x0;
+        $dst.x1 = $src.x1;
+        $dst.x2 = $src.x2;
+        $dst.x3 = $src.x3;
+        $dst.x4 = $src.x4;
+        $dst.x5 = $src.x5;
+        $dst.x6 = $src.x6;
+        $dst.x7 = $src.x7;
+        $dst.x8 = $src.x8;
+        $dst.x9 = $src.x9;
+        $dst.x10 = $src.x10;
+        $dst.x11 = $src.x11;
+        $dst.x12 = $src.x12;
+        $dst.x13 = $src.x13;
+        $dst.x14 = $src.x14;
+        $dst.x15 = $src.x15;
+        $dst.x16 = $src.x16;
+        $dst.x17 = $src.x17;
+        $dst.x18 = $src.x18;
+        $dst.x19 = $src.x19;
+        $dst.x20 = $src.x20;
+        $dst.x21 = $src.x21;
+        $dst.x22 = $src.x22;
+        $dst.x23 = $src.x23;
+        $dst.x24 = $src.x24;
+        $dst.x25 = $src.x25;
+        $dst.x26 = $src.x26;
+        $dst.x27 = $src.x27;
+        $dst.x28 = $src.x28;
+        $dst.x29 = $src.x29;
+        $dst.x30 = $src.x30;
+        $dst.sp = $src.sp;
+    };
+}
+
+impl GpRegs {
+    pub fn copy_to_raw(&self, dst: &mut RawGeneralRegs) {
+        copy_gp_regs!(self, dst);
+    }
+
+    pub fn copy_from_raw(&mut self, src: &RawGeneralRegs) {
+        copy_gp_regs!(src, self);
+    }
+}
+
+impl TryFrom<&CpuExceptionInfo> for PageFaultInfo {
+    // [`Err`] indicates that the [`CpuExceptionInfo`] is not a page fault,
+    // with no additional error information.
+    type Error = ();
+
+    fn try_from(value: &CpuExceptionInfo) -> Result<Self, ()> {
+        /// The write-not-read bit of the syndrome of data aborts.
+        const ESR_WNR: usize = 1 << 6;
+
+        // Only the translation faults, the access flag faults and the permission faults
+        // are page faults. The others, e.g., the alignment faults, are not.
+        let status = value.error_code & 0x3f;
+        if !(0b000100..=0b001111).contains(&status) {
+            return Err(());
+        }
+
+        let required_perms = match value.cpu_exception() {
+            CpuException::InstructionAbortLowerEl => VmPerms::EXEC,
+            CpuException::DataAbortLowerEl if value.error_code & ESR_WNR != 0 => VmPerms::WRITE,
+            CpuException::DataAbortLowerEl => VmPerms::READ,
+            _ => return Err(()),
+        };
+
+        Ok(PageFaultInfo {
+            address: value.page_fault_addr,
+            required_perms,
+        })
+    }
+}
+
+/// The platform string reported by `AT_PLATFORM`, which is absent like Linux.
+pub const ELF_PLATFORM: Option<&[u8]> = None;
+/// The platform string reported to the 32-bit programs, which are not supported.
+pub const COMPAT_ELF_PLATFORM: Option<&[u8]> = None;
+
+/// Returns the hardware capabilities reported by `AT_HWCAP`.
+///
+/// Only the floating-point (`HWCAP_FP`) and the Advanced SIMD (`HWCAP_ASIMD`) units,
+/// which are mandatory in AArch64, are reported.
+pub fn elf_hwcap() -> u64 {
+    const HWCAP_FP: u64 = 1 << 0;
+    const HWCAP_ASIMD: u64 = 1 << 1;
+
+    HWCAP_FP | HWCAP_ASIMD
+}
+
+/// Returns the extended hardware capabilities reported by `AT_HWCAP2`, which are none.
+pub fn elf_hwcap2() -> u64 {
+    0
+}
@@ -0,0 +1,5 @@
+// SPDX-License-Identifier: MPL-2.0
+
+pub mod cpu;
+pub mod ptrace;
+pub mod signal;
@@ -0,0 +1,136 @@
+// SPDX-License-Identifier: MPL-2.0
+
+use ostd::{cpu::UserContext, user::UserContextApi, Pod};
+
+use crate::prelude::*;
+
+/// The registers of a traced thread (`struct user_pt_regs`).
+///
+/// The tracer reads and writes the registers with `PTRACE_GETREGSET` and `PTRACE_SETREGSET`.
+#[derive(Debug, Clone, Copy, Pod, Default)]
+#[repr(C)]
+pub struct PtraceRegs {
+    pub x0: usize,
+    pub x1: usize,
+    pub x2: usize,
+    pub x3: usize,
+    pub x4: usize,
+    pub x5: usize,
+    pub x6: usize,
+    pub x7: usize,
+    pub x8: usize,
+    pub x9: usize,
+    pub x10: usize,
+    pub x11: usize,
+    pub x12: usize,
+    pub x13: usize,
+    pub x14: usize,
+    pub x15: usize,
+    pub x16: usize,
+    pub x17: usize,
+    pub x18: usize,
+    pub x19: usize,
+    pub x20: usize,
+    pub x21: usize,
+    pub x22: usize,
+    pub x23: usize,
+    pub x24: usize,
+    pub x25: usize,
+    pub x26: usize,
+    pub x27: usize,
+    pub x28: usize,
+    pub x29: usize,
+    pub x30: usize,
+    pub sp: usize,
+    pub pc: usize,
+    pub pstate: usize,
+}
+
+macro_rules! copy_ptrace_regs {
+    ($src: ident, $dst: ident) => {
+        $dst.x0 = $src.x0;
+        $dst.x1 = $src.x1;
+        $dst.x2 = $src.x2;
+        $dst.x3 = $src.x3;
+        $dst.x4 = $src.x4;
+        $dst.x5 = $src.x5;
+        $dst.x6 = $src.x6;
+        $dst.x7 = $src.x7;
+        $dst.x8 = $src.x8;
+        $dst.x9 = $src.x9;
+        $dst.x10 = $src.x10;
+        $dst.x11 = $src.x11;
+        $dst.x12 = $src.x12;
+        $dst.x13 = $src.x13;
+        $dst.x14 = $src.x14;
+        $dst.x15 = $src.x15;
+        $dst.x16 = $src.x16;
+        $dst.x17 = $src.x17;
+        $dst.x18 = $src.x18;
+        $dst.x19 = $src.x19;
+        $dst.x20 = $src.x20;
+        $dst.x21 = $src.x21;
+        $dst.x22 = $src.x22;
+        $dst.x23 = $src.x23;
+        $dst.x24 = $src.x24;
+        $dst.x25 = $src.x25;
+        $dst.x26 = $src.x26;
+        $dst.x27 = $src.x27;
+        $dst.x28 = $src.x28;
+        $dst.x29 = $src.x29;
+        $dst.x30 = $src.x30;
+        $dst.sp = $src.sp;
+    };
+}
+
+impl PtraceRegs {
+    /// Reads the registers from the user context.
+    ///
+    /// The system call number is always in `x8`, so `_syscall_num` is not used.
+    pub fn from_context(user_ctx: &UserContext, _syscall_num: Option<usize>) -> Self {
+        let regs = user_ctx.general_regs();
+        let mut ptrace_regs = Self {
+            pc: user_ctx.instruction_pointer(),
+            pstate: user_ctx.pstate(),
+            ..Default::default()
+        };
+        copy_ptrace_regs!(regs, ptrace_regs);
+        ptrace_regs
+    }
+
+    /// Writes the registers to the user context.
+    ///
+    /// Only the condition flags of `pstate` take effect.
+    pub fn write_to_context(&self, user_ctx: &mut UserContext) {
+        user_ctx.set_instruction_pointer(self.pc);
+        user_ctx.set_pstate(self.pstate);
+        let regs = user_ctx.general_regs_mut();
+        copy_ptrace_regs!(self, regs);
+    }
+
+    /// Returns the system call number, which can be changed by the tracer at syscall-entry stops.
+    pub fn syscall_num(&self) -> usize {
+        self.x8
+    }
+
+    /// Enables or disables single-stepping.
+    ///
+    /// Single-stepping cannot be enabled, since the software step exceptions are not
+    /// supported yet.
+    pub fn set_single_step(&mut self, is_enabled: bool) -> Result<()> {
+        if is_enabled {
+            return_errno_with_message!(Errno::EIO, "single-stepping is not supported");
+        }
+        Ok(())
+    }
+
+    /// Reads the word at `offset` in the user area, which is not supported on AArch64.
+    pub fn read_user(&self, _offset: usize) -> Result<usize> {
+        return_errno_with_message!(Errno::EIO, "the user area is not supported");
+    }
+
+    /// Writes the word at `offset` in the user area, which is not supported on AArch64.
+    pub fn write_user(&mut self, _offset: usize, _value: usize) -> Result<()> {
+        return_errno_with_message!(Errno::EIO, "the user area is not supported");
+    }
+}
@@ -0,0 +1,48 @@
+// SPDX-License-Identifier: MPL-2.0
+
+use ostd::cpu::{CpuException, CpuExceptionInfo, UserContext};
+
+use crate::process::signal::{
+    constants::*, sig_num::SigNum, signals::fault::FaultSignal, SignalContext,
+};
+
+impl SignalContext for UserContext {
+    fn set_arguments(&mut self, sig_num: SigNum, siginfo_addr: usize, ucontext_addr: usize) {
+        self.set_x0(sig_num.as_u8() as usize);
+        self.set_x1(siginfo_addr);
+        self.set_x2(ucontext_addr);
+    }
+}
+
+impl From<&CpuExceptionInfo> for FaultSignal {
+    fn from(trap_info: &CpuExceptionInfo) -> Self {
+        let (num, code, addr) = match trap_info.cpu_exception() {
+            CpuException::Unknown
+            | CpuException::IllegalExecutionState
+            | CpuException::SysRegTrap
+            | CpuException::WfiWfe => (SIGILL, ILL_ILLOPC, None),
+            CpuException::PcAlignmentFault | CpuException::SpAlignmentFault => {
+                (SIGBUS, BUS_ADRALN, Some(trap_info.page_fault_addr as u64))
+            }
+            CpuException::InstructionAbortLowerEl | CpuException::DataAbortLowerEl => {
+                // The permission faults are raised on the mapped pages, while the
+                // translation faults and the others are raised on the unmapped ones.
+                const DFSC_PERMISSION_FAULT: usize = 0b001100;
+                let code = if trap_info.error_code & 0x3c == DFSC_PERMISSION_FAULT {
+                    SEGV_ACCERR
+                } else {
+                    SEGV_MAPERR
+                };
+                (SIGSEGV, code, Some(trap_info.page_fault_addr as u64))
+            }
+            CpuException::FpException => (SIGFPE, FPE_FLTINV, None),
+            CpuException::BreakpointLowerEl | CpuException::Brk64 => (SIGTRAP, TRAP_BRKPT, None),
+            CpuException::SoftwareStepLowerEl | CpuException::WatchpointLowerEl => {
+                (SIGTRAP, TRAP_TRACE, None)
+            }
+            CpuException::SError => (SIGBUS, BUS_OBJERR, None),
+            CpuException::Svc64 => panic!("Exception cannot be a signal"),
+        };
+        FaultSignal::new(num, code, addr)
+    }
+}
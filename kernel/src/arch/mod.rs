@@ -1,10 +1,14 @@
 // SPDX-License-Identifier: MPL-2.0
 
+#[cfg(target_arch = "aarch64")]
+mod aarch64;
 #[cfg(target_arch = "riscv64")]
 mod riscv;
 #[cfg(target_arch = "x86_64")]
 mod x86;
 
+#[cfg(target_arch = "aarch64")]
+pub use aarch64::*;
 #[cfg(target_arch = "riscv64")]
 pub use riscv::*;
 #[cfg(target_arch = "x86_64")]
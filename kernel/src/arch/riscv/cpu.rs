@@ -5,7 +5,10 @@ use ostd::{
     Pod,
 };
 
-use crate::{cpu::LinuxAbi, thread::exception::PageFaultInfo, vm::perms::VmPerms};
+use crate::{
+    cpu::LinuxAbi, process::posix_thread::seccomp::AUDIT_ARCH, thread::exception::PageFaultInfo,
+    vm::perms::VmPerms,
+};
 
 impl LinuxAbi for UserContext {
     fn syscall_num(&self) -> usize {
@@ -38,6 +41,13 @@ impl LinuxAbi for UserContext {
     fn tls_pointer(&self) -> usize {
         self.tp()
     }
+    fn frame_pointer(&self) -> usize {
+        self.s0()
+    }
+
+    fn syscall_arch(&self) -> u32 {
+        AUDIT_ARCH
+    }
 }
 
 /// General-purpose registers.
@@ -146,3 +156,23 @@ impl TryFrom<&CpuExceptionInfo> for PageFaultInfo {
         })
     }
 }
+
+/// The platform string reported by `AT_PLATFORM`, which is absent like Linux.
+pub const ELF_PLATFORM: Option<&[u8]> = None;
+/// The platform string reported to the 32-bit programs, which are not supported.
+pub const COMPAT_ELF_PLATFORM: Option<&[u8]> = None;
+
+/// Returns the hardware capabilities reported by `AT_HWCAP`.
+///
+/// Like Linux, each bit represents a single-letter ISA extension. Since the kernel targets
+/// `rv64gc`, the `IMAFDC` extensions are reported.
+pub fn elf_hwcap() -> u64 {
+    b"imafdc"
+        .iter()
+        .fold(0, |hwcap, extension| hwcap | (1 << (extension - b'a')))
+}
+
+/// Returns the extended hardware capabilities reported by `AT_HWCAP2`, which are none.
+pub fn elf_hwcap2() -> u64 {
+    0
+}
@@ -0,0 +1,128 @@
+// SPDX-License-Identifier: MPL-2.0
+
+use ostd::{cpu::UserContext, user::UserContextApi, Pod};
+
+use crate::prelude::*;
+
+/// The registers of a traced thread (`struct user_regs_struct`).
+///
+/// The tracer reads and writes the registers with `PTRACE_GETREGSET` and `PTRACE_SETREGSET`.
+#[derive(Debug, Clone, Copy, Pod, Default)]
+#[repr(C)]
+pub struct PtraceRegs {
+    pub pc: usize,
+    pub ra: usize,
+    pub sp: usize,
+    pub gp: usize,
+    pub tp: usize,
+    pub t0: usize,
+    pub t1: usize,
+    pub t2: usize,
+    pub s0: usize,
+    pub s1: usize,
+    pub a0: usize,
+    pub a1: usize,
+    pub a2: usize,
+    pub a3: usize,
+    pub a4: usize,
+    pub a5: usize,
+    pub a6: usize,
+    pub a7: usize,
+    pub s2: usize,
+    pub s3: usize,
+    pub s4: usize,
+    pub s5: usize,
+    pub s6: usize,
+    pub s7: usize,
+    pub s8: usize,
+    pub s9: usize,
+    pub s10: usize,
+    pub s11: usize,
+    pub t3: usize,
+    pub t4: usize,
+    pub t5: usize,
+    pub t6: usize,
+}
+
+macro_rules! copy_ptrace_regs {
+    ($src: ident, $dst: ident) => {
+        $dst.ra = $src.ra;
+        $dst.sp = $src.sp;
+        $dst.gp = $src.gp;
+        $dst.tp = $src.tp;
+        $dst.t0 = $src.t0;
+        $dst.t1 = $src.t1;
+        $dst.t2 = $src.t2;
+        $dst.s0 = $src.s0;
+        $dst.s1 = $src.s1;
+        $dst.a0 = $src.a0;
+        $dst.a1 = $src.a1;
+        $dst.a2 = $src.a2;
+        $dst.a3 = $src.a3;
+        $dst.a4 = $src.a4;
+        $dst.a5 = $src.a5;
+        $dst.a6 = $src.a6;
+        $dst.a7 = $src.a7;
+        $dst.s2 = $src.s2;
+        $dst.s3 = $src.s3;
+        $dst.s4 = $src.s4;
+        $dst.s5 = $src.s5;
+        $dst.s6 = $src.s6;
+        $dst.s7 = $src.s7;
+        $dst.s8 = $src.s8;
+        $dst.s9 = $src.s9;
+        $dst.s10 = $src.s10;
+        $dst.s11 = $src.s11;
+        $dst.t3 = $src.t3;
+        $dst.t4 = $src.t4;
+        $dst.t5 = $src.t5;
+        $dst.t6 = $src.t6;
+    };
+}
+
+impl PtraceRegs {
+    /// Reads the registers from the user context.
+    ///
+    /// The system call number is always in `a7`, so `_syscall_num` is not used.
+    pub fn from_context(user_ctx: &UserContext, _syscall_num: Option<usize>) -> Self {
+        let regs = user_ctx.general_regs();
+        let mut ptrace_regs = Self {
+            pc: user_ctx.instruction_pointer(),
+            ..Default::default()
+        };
+        copy_ptrace_regs!(regs, ptrace_regs);
+        ptrace_regs
+    }
+
+    /// Writes the registers to the user context.
+    pub fn write_to_context(&self, user_ctx: &mut UserContext) {
+        user_ctx.set_instruction_pointer(self.pc);
+        let regs = user_ctx.general_regs_mut();
+        copy_ptrace_regs!(self, regs);
+    }
+
+    /// Returns the system call number, which can be changed by the tracer at syscall-entry stops.
+    pub fn syscall_num(&self) -> usize {
+        self.a7
+    }
+
+    /// Enables or disables single-stepping.
+    ///
+    /// Single-stepping cannot be enabled, since RISC-V has no hardware support for it.
+    pub fn set_single_step(&mut self, is_enabled: bool) -> Result<()> {
+        if is_enabled {
+            return_errno_with_message!(Errno::EIO, "single-stepping is not supported");
+        }
+        Ok(())
+    }
+
+    /// Reads the word at `offset` in the user area, which is not supported on RISC-V.
+    pub fn read_user(&self, _offset: usize) -> Result<usize> {
+        return_errno_with_message!(Errno::EIO, "the user area is not supported");
+    }
+
+    /// Writes the word at `offset` in the user area, which is not supported on RISC-V.
+    pub fn write_user(&mut self, _offset: usize, _value: usize) -> Result<()> {
+        return_errno_with_message!(Errno::EIO, "the user area is not supported");
+    }
+}
@@ -7,14 +7,22 @@ use alloc::{
 };
 
 use ostd::{
-    cpu::{cpuid, CpuException, CpuExceptionInfo, RawGeneralRegs, UserContext},
+    cpu::{cpuid, freq, CpuException, CpuExceptionInfo, CpuId, RawGeneralRegs, UserContext},
     Pod,
 };
 
-use crate::{cpu::LinuxAbi, thread::exception::PageFaultInfo, vm::perms::VmPerms};
+use crate::{
+    cpu::LinuxAbi,
+    process::posix_thread::seccomp::{AUDIT_ARCH, COMPAT_AUDIT_ARCH},
+    thread::exception::PageFaultInfo,
+    vm::perms::VmPerms,
+};
 
 impl LinuxAbi for UserContext {
     fn syscall_num(&self) -> usize {
+        if self.is_compat_syscall() {
+            return self.rax() as u32 as usize;
+        }
         self.rax()
     }
 
@@ -31,6 +39,18 @@ impl LinuxAbi for UserContext {
     }
 
     fn syscall_args(&self) -> [usize; 6] {
+        // The 32-bit system calls take the arguments from the low halves of other registers.
+        if self.is_compat_syscall() {
+            return [
+                self.rbx(),
+                self.rcx(),
+                self.rdx(),
+                self.rsi(),
+                self.rdi(),
+                self.rbp(),
+            ]
+            .map(|arg| arg as u32 as usize);
+        }
         [
             self.rdi(),
             self.rsi(),
@@ -48,6 +68,17 @@ impl LinuxAbi for UserContext {
     fn tls_pointer(&self) -> usize {
         self.fsbase()
     }
+    fn frame_pointer(&self) -> usize {
+        self.rbp()
+    }
+
+    fn syscall_arch(&self) -> u32 {
+        if self.is_compat_syscall() {
+            COMPAT_AUDIT_ARCH
+        } else {
+            AUDIT_ARCH
+        }
+    }
 }
 
 /// General-purpose registers.
@@ -96,18 +127,20 @@ macro_rules! copy_gp_regs {
         $dst.r15 = $src.r15;
         $dst.rip = $src.rip;
         $dst.rflags = $src.rflags;
-        $dst.fsbase = $src.fsbase;
-        $dst.gsbase = $src.gsbase;
     };
 }
 
 impl GpRegs {
     pub fn copy_to_raw(&self, dst: &mut RawGeneralRegs) {
+        // Like Linux, the FS and GS bases are not restored from the signal frame, so that a
+        // signal handler can change them (e.g., with `arch_prctl`) and keep the changes.
         copy_gp_regs!(self, dst);
     }
 
     pub fn copy_from_raw(&mut self, src: &RawGeneralRegs) {
         copy_gp_regs!(src, self);
+        self.fsbase = src.fsbase;
+        self.gsbase = src.gsbase;
     }
 }
 
@@ -181,7 +214,7 @@ impl CpuInfo {
             model_name: Self::get_model_name(),
             stepping: Self::get_stepping(),
             microcode: Self::get_microcode(),
-            cpu_mhz: Self::get_clock_speed().unwrap_or(0),
+            cpu_mhz: Self::get_clock_speed(processor_id),
             cache_size: Self::get_cache_size().unwrap_or(0),
             tlb_size: Self::get_tlb_size().unwrap_or(0),
             physical_id: Self::get_physical_id().unwrap_or(0),
@@ -287,14 +320,12 @@ impl CpuInfo {
         cpuid.ecx
     }
 
-    fn get_clock_speed() -> Option<u32> {
-        let cpuid = cpuid::CpuId::new();
-        let tsc_info = cpuid.get_tsc_info()?;
-        Some(
-            (tsc_info.tsc_frequency().unwrap_or(0) / 1_000_000)
-                .try_into()
-                .unwrap(),
-        )
+    /// Gets the effective frequency of the processor in MHz.
+    fn get_clock_speed(processor_id: u32) -> u32 {
+        let Ok(cpu_id) = CpuId::try_from(processor_id as usize) else {
+            return 0;
+        };
+        (freq::cur_freq_khz(cpu_id) / 1000) as u32
     }
 
     /// Get cache size in KB
@@ -524,3 +555,27 @@ impl CpuInfo {
         " ".to_string()
     }
 }
+
+/// The platform string reported by `AT_PLATFORM`, including the ending null byte.
+pub const ELF_PLATFORM: Option<&[u8]> = Some(b"x86_64\0");
+/// The platform string reported by `AT_PLATFORM` to the 32-bit programs.
+pub const COMPAT_ELF_PLATFORM: Option<&[u8]> = Some(b"i686\0");
+
+/// Returns the hardware capabilities reported by `AT_HWCAP`.
+///
+/// Like Linux, they are the feature flags in the EDX register of the CPUID leaf 1.
+pub fn elf_hwcap() -> u64 {
+    cpuid::cpuid!(0x1).edx as u64
+}
+
+/// Returns the extended hardware capabilities reported by `AT_HWCAP2`.
+pub fn elf_hwcap2() -> u64 {
+    /// The `FSGSBASE` instructions are enabled for the user space.
+    const HWCAP2_FSGSBASE: u64 = 1 << 1;
+
+    if ostd::arch::has_fsgsbase() {
+        HWCAP2_FSGSBASE
+    } else {
+        0
+    }
+}
@@ -0,0 +1,179 @@
+// SPDX-License-Identifier: MPL-2.0
+
+use core::{mem::size_of, ops::Range};
+
+use ostd::{cpu::UserContext, Pod};
+
+use crate::prelude::*;
+
+/// The registers of a traced thread (`struct user_regs_struct`).
+///
+/// The tracer reads and writes the registers with `PTRACE_GETREGS` and `PTRACE_SETREGS`.
+#[derive(Debug, Clone, Copy, Pod, Default)]
+#[repr(C)]
+pub struct PtraceRegs {
+    pub r15: usize,
+    pub r14: usize,
+    pub r13: usize,
+    pub r12: usize,
+    pub rbp: usize,
+    pub rbx: usize,
+    pub r11: usize,
+    pub r10: usize,
+    pub r9: usize,
+    pub r8: usize,
+    pub rax: usize,
+    pub rcx: usize,
+    pub rdx: usize,
+    pub rsi: usize,
+    pub rdi: usize,
+    pub orig_rax: usize,
+    pub rip: usize,
+    pub cs: usize,
+    pub eflags: usize,
+    pub rsp: usize,
+    pub ss: usize,
+    pub fs_base: usize,
+    pub gs_base: usize,
+    pub ds: usize,
+    pub es: usize,
+    pub fs: usize,
+    pub gs: usize,
+}
+
+/// The code segment selector of the user space, which is the same as Linux.
+const USER_CS: usize = 0x33;
+/// The stack segment selector of the user space, which is the same as Linux.
+const USER_SS: usize = 0x2b;
+
+/// The trap flag in `RFLAGS`, which enables single-stepping.
+const TRAP_FLAG: usize = 1 << 8;
+/// The flags in `RFLAGS` that can be changed by the tracer.
+///
+/// These are the status flags, the trap flag, the direction flag, and the alignment check flag.
+const CHANGEABLE_FLAGS: usize = 0x40dd5;
+
+/// The offsets of the debug registers in the user area (`struct user`).
+const DEBUG_REGS: Range<usize> = 848..912;
+
+impl PtraceRegs {
+    /// Reads the registers from the user context.
+    ///
+    /// If the thread is stopped in a system call, `syscall_num` should be the system call number,
+    /// which is exposed as `orig_rax`.
+    pub fn from_context(user_ctx: &UserContext, syscall_num: Option<usize>) -> Self {
+        let regs = user_ctx.general_regs();
+        Self {
+            r15: regs.r15,
+            r14: regs.r14,
+            r13: regs.r13,
+            r12: regs.r12,
+            rbp: regs.rbp,
+            rbx: regs.rbx,
+            r11: regs.r11,
+            r10: regs.r10,
+            r9: regs.r9,
+            r8: regs.r8,
+            rax: regs.rax,
+            rcx: regs.rcx,
+            rdx: regs.rdx,
+            rsi: regs.rsi,
+            rdi: regs.rdi,
+            orig_rax: syscall_num.unwrap_or(usize::MAX),
+            rip: regs.rip,
+            cs: USER_CS,
+            eflags: regs.rflags,
+            rsp: regs.rsp,
+            ss: USER_SS,
+            fs_base: regs.fsbase,
+            gs_base: regs.gsbase,
+            ds: 0,
+            es: 0,
+            fs: 0,
+            gs: 0,
+        }
+    }
+
+    /// Writes the registers to the user context.
+    ///
+    /// The segment registers are ignored, and only some of the flags in `RFLAGS` are changed.
+    pub fn write_to_context(&self, user_ctx: &mut UserContext) {
+        let regs = user_ctx.general_regs_mut();
+        regs.r15 = self.r15;
+        regs.r14 = self.r14;
+        regs.r13 = self.r13;
+        regs.r12 = self.r12;
+        regs.rbp = self.rbp;
+        regs.rbx = self.rbx;
+        regs.r11 = self.r11;
+        regs.r10 = self.r10;
+        regs.r9 = self.r9;
+        regs.r8 = self.r8;
+        regs.rax = self.rax;
+        regs.rcx = self.rcx;
+        regs.rdx = self.rdx;
+        regs.rsi = self.rsi;
+        regs.rdi = self.rdi;
+        regs.rip = self.rip;
+        regs.rflags = (regs.rflags & !CHANGEABLE_FLAGS) | (self.eflags & CHANGEABLE_FLAGS);
+        regs.rsp = self.rsp;
+        regs.fsbase = self.fs_base;
+        regs.gsbase = self.gs_base;
+    }
+
+    /// Returns the system call number, which can be changed by the tracer at syscall-entry stops.
+    pub fn syscall_num(&self) -> usize {
+        self.orig_rax
+    }
+
+    /// Enables or disables single-stepping.
+    pub fn set_single_step(&mut self, is_enabled: bool) -> Result<()> {
+        if is_enabled {
+            self.eflags |= TRAP_FLAG;
+        } else {
+            self.eflags &= !TRAP_FLAG;
+        }
+        Ok(())
+    }
+
+    /// Reads the word at `offset` in the user area (`struct user`).
+    ///
+    /// Only the registers and the debug registers can be read. Since hardware breakpoints are
+    /// not supported, the debug registers are always zero.
+    pub fn read_user(&self, offset: usize) -> Result<usize> {
+        check_user_offset(offset)?;
+
+        if offset < size_of::<Self>() {
+            Ok(usize::from_bytes(
+                &self.as_bytes()[offset..offset + size_of::<usize>()],
+            ))
+        } else {
+            Ok(0)
+        }
+    }
+
+    /// Writes the word at `offset` in the user area (`struct user`).
+    ///
+    /// Only the registers and the debug registers can be written, and the debug registers can
+    /// only be cleared.
+    pub fn write_user(&mut self, offset: usize, value: usize) -> Result<()> {
+        check_user_offset(offset)?;
+
+        if offset < size_of::<Self>() {
+            self.as_bytes_mut()[offset..offset + size_of::<usize>()]
+                .copy_from_slice(value.as_bytes());
+        } else if value != 0 {
+            return_errno_with_message!(Errno::EIO, "hardware breakpoints are not supported");
+        }
+        Ok(())
+    }
+}
+
+fn check_user_offset(offset: usize) -> Result<()> {
+    if offset % size_of::<usize>() != 0
+        || (offset >= size_of::<PtraceRegs>() && !DEBUG_REGS.contains(&offset))
+    {
+        return_errno_with_message!(Errno::EIO, "the offset in the user area is invalid");
+    }
+    Ok(())
+}
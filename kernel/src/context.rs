@@ -38,7 +38,7 @@ impl Context<'_> {
 /// The user's memory space of the current task.
 ///
 /// It provides methods to read from or write to the user space efficiently.
-pub struct CurrentUserSpace<'a>(&'a VmSpace);
+pub struct CurrentUserSpace(Arc<VmSpace>);
 
 /// Gets the [`CurrentUserSpace`] from the current task.
 ///
@@ -51,7 +51,7 @@ macro_rules! current_userspace {
     };
 }
 
-impl<'a> CurrentUserSpace<'a> {
+impl CurrentUserSpace {
     /// Creates a new `CurrentUserSpace` from the specified task.
     ///
     /// This method is _not_ recommended for use, as it does not verify whether the provided
@@ -65,7 +65,7 @@ impl<'a> CurrentUserSpace<'a> {
     /// # Panics
     ///
     /// This method will panic in debug builds if the specified `task` is not the current task.
-    pub fn new(task: &'a Task) -> Self {
+    pub fn new(task: &Task) -> Self {
         let user_space = task.user_space().unwrap();
         debug_assert!(Arc::ptr_eq(
             task.user_space().unwrap(),
@@ -21,4 +21,11 @@ pub trait LinuxAbi {
 
     /// Get thread-local storage pointer
     fn tls_pointer(&self) -> usize;
+
+    /// Get frame pointer
+    fn frame_pointer(&self) -> usize;
+
+    /// Get the audit architecture of syscall (`AUDIT_ARCH_*`), which tells the ABI that the
+    /// syscall follows
+    fn syscall_arch(&self) -> u32;
 }
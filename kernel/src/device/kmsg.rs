@@ -0,0 +1,225 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! The `/dev/kmsg` device, which gives access to the kernel message buffer.
+//!
+//! Each opened file reads the entries in the buffer one by one, starting from the oldest one.
+//! The entries are formatted in the same way as Linux, i.e., `PRIO,SEQ,TS_USEC,FLAGS;TEXT`
+//! followed by the subsystem tag in a continuation line ` SUBSYSTEM=TAG`. If the next entry
+//! to read has been overwritten, the read fails with `EPIPE` and the file continues from the
+//! oldest entry. Writing the file adds an entry, whose text can start with `<PRIO>` to specify
+//! the syslog priority.
+//!
+//! Reference: <https://www.kernel.org/doc/Documentation/ABI/testing/dev-kmsg>
+
+use alloc::format;
+use core::{
+    fmt::Write,
+    sync::atomic::{AtomicU64, Ordering},
+    time::Duration,
+};
+
+use aster_logger::kmsg::{self, KmsgEntry, LOG_USER};
+use ostd::sync::WaitQueue;
+use spin::Once;
+
+use super::*;
+use crate::{
+    events::IoEvents,
+    fs::inode_handle::FileIo,
+    prelude::*,
+    process::signal::{PollHandle, Pollable, Pollee},
+    thread::work_queue::{submit_work_item, work_item::WorkItem, WorkPriority},
+    time::{
+        clocks::MonotonicClock,
+        timer::{Timeout, Timer},
+    },
+};
+
+/// The maximum length of a message written to the device.
+const MAX_WRITE_LEN: usize = 1024;
+
+/// The syslog level of the messages written without a priority, which is the same as Linux.
+const DEFAULT_MESSAGE_LOGLEVEL: u8 = 4;
+
+/// The interval to check the new entries and notify the readers.
+const NOTIFY_INTERVAL: Duration = Duration::from_millis(50);
+
+pub struct Kmsg;
+
+impl Device for Kmsg {
+    fn type_(&self) -> DeviceType {
+        DeviceType::CharDevice
+    }
+
+    fn id(&self) -> DeviceId {
+        // The same value as Linux
+        DeviceId::new(1, 11)
+    }
+
+    fn open(&self) -> Result<Option<Arc<dyn FileIo>>> {
+        let file = Arc::new(KmsgFile {
+            seq: Mutex::new(kmsg::first_seq()),
+            pollee: Pollee::new(),
+        });
+        OPEN_FILES.lock().push(Arc::downgrade(&file));
+        start_notifier();
+
+        Ok(Some(file))
+    }
+}
+
+/// An opened `/dev/kmsg` file.
+struct KmsgFile {
+    /// The sequence number of the next entry to read.
+    seq: Mutex<u64>,
+    pollee: Pollee,
+}
+
+impl KmsgFile {
+    fn try_read(&self, writer: &mut VmWriter) -> Result<usize> {
+        let mut seq = self.seq.lock();
+
+        let Some(entry) = kmsg::read(*seq) else {
+            let first_seq = kmsg::first_seq();
+            if *seq < first_seq {
+                *seq = first_seq;
+                return_errno_with_message!(Errno::EPIPE, "the entries have been overwritten");
+            }
+            return_errno_with_message!(Errno::EAGAIN, "no entries are available");
+        };
+
+        let record = format_record(&entry);
+        if record.len() > writer.avail() {
+            return_errno_with_message!(Errno::EINVAL, "the buffer is too small for the entry");
+        }
+        writer.write_fallible(&mut record.as_bytes().into())?;
+        *seq += 1;
+
+        Ok(record.len())
+    }
+
+    fn check_io_events(&self) -> IoEvents {
+        if *self.seq.lock() < kmsg::next_seq() {
+            IoEvents::IN | IoEvents::OUT
+        } else {
+            IoEvents::OUT
+        }
+    }
+}
+
+impl Pollable for KmsgFile {
+    fn poll(&self, mask: IoEvents, poller: Option<&mut PollHandle>) -> IoEvents {
+        self.pollee
+            .poll_with(mask, poller, || self.check_io_events())
+    }
+}
+
+impl FileIo for KmsgFile {
+    fn read(&self, writer: &mut VmWriter) -> Result<usize> {
+        // TODO: Deal with nonblocking reads.
+        self.wait_events(IoEvents::IN, None, || self.try_read(writer))
+    }
+
+    fn write(&self, reader: &mut VmReader) -> Result<usize> {
+        let len = reader.remain();
+        if len > MAX_WRITE_LEN {
+            return_errno_with_message!(Errno::EINVAL, "the message is too long");
+        }
+        let mut buf = vec![0; len];
+        reader.read_fallible(&mut buf.as_mut_slice().into())?;
+
+        let message = String::from_utf8_lossy(&buf);
+        let (prio, text) = parse_prio(&message);
+        aster_logger::log_user_message(prio, text);
+
+        Ok(len)
+    }
+}
+
+/// Parses the `<PRIO>` prefix of a message written by a user program.
+fn parse_prio(message: &str) -> (u8, &str) {
+    let parsed = message.strip_prefix('<').and_then(|rest| {
+        let (prio, text) = rest.split_once('>')?;
+        Some((prio.parse::<u8>().ok()?, text))
+    });
+    match parsed {
+        // The user programs cannot use the kernel facility, which is the same as Linux.
+        Some((prio, text)) if prio & !0x7 != 0 => (prio, text),
+        Some((prio, text)) => (LOG_USER | prio, text),
+        None => (LOG_USER | DEFAULT_MESSAGE_LOGLEVEL, message),
+    }
+}
+
+/// Formats the entry as a record read from `/dev/kmsg`.
+fn format_record(entry: &KmsgEntry) -> String {
+    let mut record = format!(
+        "{},{},{},-;",
+        entry.prio(),
+        entry.seq(),
+        entry.timestamp().as_micros()
+    );
+    write_escaped(&mut record, entry.text());
+    record.push('\n');
+    if !entry.subsystem().is_empty() {
+        record.push_str(" SUBSYSTEM=");
+        write_escaped(&mut record, entry.subsystem());
+        record.push('\n');
+    }
+    record
+}
+
+/// Writes the text with the non-printable characters escaped as `\xNN`, like Linux.
+fn write_escaped(output: &mut String, text: &str) {
+    for c in text.chars() {
+        if c == '\\' || (c as u32) < 0x20 || c == '\x7f' {
+            let _ = write!(output, "\\x{:02x}", c as u32);
+        } else {
+            output.push(c);
+        }
+    }
+}
+
+/// The wait queue of the tasks that wait for the new entries, e.g., in `syslog(2)`.
+pub(crate) static NEW_ENTRY_WAIT_QUEUE: WaitQueue = WaitQueue::new();
+
+static OPEN_FILES: Mutex<Vec<Weak<KmsgFile>>> = Mutex::new(Vec::new());
+
+static NOTIFIER: Once<Arc<Timer>> = Once::new();
+
+/// The sequence number of the next entry when the readers are notified last time.
+static NOTIFIED_SEQ: AtomicU64 = AtomicU64::new(0);
+
+/// Starts to notify the readers of the new entries periodically.
+///
+/// The readers are not notified when the entries are added, since the messages can be
+/// logged while holding any locks, e.g., the ones of the scheduler.
+pub(crate) fn start_notifier() {
+    NOTIFIER.call_once(|| {
+        // The timer callback is executed in the interrupt context, so the readers are notified
+        // in a work item.
+        let work_item = WorkItem::new(Box::new(notify_readers));
+        let timer = MonotonicClock::timer_manager().create_timer(move || {
+            let next_seq = kmsg::next_seq();
+            if NOTIFIED_SEQ.swap(next_seq, Ordering::Relaxed) != next_seq {
+                submit_work_item(work_item.clone(), WorkPriority::Normal);
+            }
+        });
+        timer.set_interval(NOTIFY_INTERVAL);
+        timer.set_timeout(Timeout::After(NOTIFY_INTERVAL));
+        timer
+    });
+}
+
+fn notify_readers() {
+    let mut open_files = OPEN_FILES.lock();
+    open_files.retain(|file| {
+        let Some(file) = file.upgrade() else {
+            return false;
+        };
+        file.pollee.notify(IoEvents::IN);
+        true
+    });
+    drop(open_files);
+
+    NEW_ENTRY_WAIT_QUEUE.wake_all();
+}
@@ -2,11 +2,16 @@
 
 use cfg_if::cfg_if;
 
+pub mod kmsg;
+mod netfilter;
 mod null;
+#[cfg(target_arch = "x86_64")]
+pub mod ptp;
 mod pty;
 mod random;
 mod shm;
 pub mod tty;
+mod tun;
 mod urandom;
 mod zero;
 
@@ -54,6 +59,16 @@ pub fn init() -> Result<()> {
     add_node(random, "random")?;
     let urandom = Arc::new(urandom::Urandom);
     add_node(urandom, "urandom")?;
+    let kmsg = Arc::new(kmsg::Kmsg);
+    add_node(kmsg, "kmsg")?;
+    let netfilter = Arc::new(netfilter::NetFilter);
+    add_node(netfilter, "netfilter")?;
+    let tun = Arc::new(tun::TunDevice);
+    add_node(tun, "net/tun")?;
+    #[cfg(target_arch = "x86_64")]
+    if ptp::is_available() {
+        add_node(Arc::new(ptp::PtpDevice), "ptp0")?;
+    }
     pty::init()?;
     shm::init()?;
     Ok(())
@@ -78,6 +93,7 @@ pub fn get_device(dev: usize) -> Result<Arc<dyn Device>> {
         (5, 0) => Ok(Arc::new(tty::TtyDevice)),
         (1, 8) => Ok(Arc::new(random::Random)),
         (1, 9) => Ok(Arc::new(urandom::Urandom)),
+        (1, 11) => Ok(Arc::new(kmsg::Kmsg)),
         _ => return_errno_with_message!(Errno::EINVAL, "unsupported device"),
     }
 }
@@ -0,0 +1,212 @@
+// SPDX-License-Identifier: MPL-2.0
+
+#![allow(unused_variables)]
+
+//! The `/dev/netfilter` device, which configures the packet filter.
+//!
+//! The device accepts the following ioctls:
+//!  - `NFADDRULE`, which appends the rule pointed to by the argument;
+//!  - `NFFLUSH`, which removes all the rules of the hook specified by the argument.
+//!
+//! Both ioctls require the `CAP_NET_ADMIN` capability.
+
+use aster_bigtcp::{
+    iface::{FilterHook, FilterVerdict},
+    wire::{IpCidr, IpProtocol, IpVersion, Ipv4Address, Ipv4Cidr, Ipv6Address, Ipv6Cidr},
+};
+
+use super::*;
+use crate::{
+    current_userspace,
+    events::IoEvents,
+    fs::{inode_handle::FileIo, utils::IoctlCmd},
+    net::iface::{add_filter_rule, flush_filter_rules, FilterRule},
+    prelude::*,
+    process::{
+        credentials::capabilities::CapSet,
+        posix_thread::AsPosixThread,
+        signal::{PollHandle, Pollable},
+    },
+    util::net::CSocketAddrFamily,
+};
+
+pub struct NetFilter;
+
+impl Device for NetFilter {
+    fn type_(&self) -> DeviceType {
+        DeviceType::CharDevice
+    }
+
+    fn id(&self) -> DeviceId {
+        // Linux does not have this device, so a misc minor number for local use is chosen.
+        DeviceId::new(10, 240)
+    }
+
+    fn open(&self) -> Result<Option<Arc<dyn FileIo>>> {
+        Ok(Some(Arc::new(NetFilter)))
+    }
+}
+
+impl Pollable for NetFilter {
+    fn poll(&self, mask: IoEvents, poller: Option<&mut PollHandle>) -> IoEvents {
+        let events = IoEvents::IN | IoEvents::OUT;
+        events & mask
+    }
+}
+
+impl FileIo for NetFilter {
+    fn read(&self, writer: &mut VmWriter) -> Result<usize> {
+        return_errno_with_message!(Errno::EINVAL, "the packet filter cannot be read");
+    }
+
+    fn write(&self, reader: &mut VmReader) -> Result<usize> {
+        return_errno_with_message!(Errno::EINVAL, "the packet filter cannot be written");
+    }
+
+    fn ioctl(&self, cmd: IoctlCmd, arg: usize) -> Result<i32> {
+        match cmd {
+            IoctlCmd::NFADDRULE => {
+                check_net_admin()?;
+                let c_rule: CNfRule = current_userspace!().read_val(arg)?;
+                add_filter_rule(c_rule.try_into()?)?;
+                Ok(0)
+            }
+            IoctlCmd::NFFLUSH => {
+                check_net_admin()?;
+                flush_filter_rules(hook_from_c(arg as u32)?);
+                Ok(0)
+            }
+            _ => return_errno_with_message!(Errno::ENOTTY, "the ioctl is not supported"),
+        }
+    }
+}
+
+fn check_net_admin() -> Result<()> {
+    let current = current_thread!();
+    let posix_thread = current.as_posix_thread().unwrap();
+
+    if !posix_thread
+        .credentials()
+        .effective_capset()
+        .contains(CapSet::NET_ADMIN)
+    {
+        return_errno_with_message!(
+            Errno::EPERM,
+            "configuring the packet filter requires the CAP_NET_ADMIN capability"
+        );
+    }
+
+    Ok(())
+}
+
+/// A rule of the packet filter in the user space.
+///
+/// A zero prefix length, a zero port, or a zero protocol matches all the packets. The ports are in
+/// network byte order.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Pod)]
+struct CNfRule {
+    hook: u32,
+    verdict: u32,
+    ifindex: u32,
+    family: u16,
+    protocol: u8,
+    _pad: u8,
+    src_addr: [u8; 16],
+    dst_addr: [u8; 16],
+    src_prefix_len: u8,
+    dst_prefix_len: u8,
+    src_port: u16,
+    dst_port: u16,
+    _pad2: u16,
+}
+
+// The hooks, which use the same values as `NF_INET_*` in Linux.
+const NF_INET_PRE_ROUTING: u32 = 0;
+const NF_INET_LOCAL_IN: u32 = 1;
+const NF_INET_LOCAL_OUT: u32 = 3;
+
+// The verdicts, which use the same values as `NF_*` in Linux.
+const NF_DROP: u32 = 0;
+const NF_ACCEPT: u32 = 1;
+
+fn hook_from_c(hook: u32) -> Result<FilterHook> {
+    match hook {
+        NF_INET_PRE_ROUTING => Ok(FilterHook::Prerouting),
+        NF_INET_LOCAL_IN => Ok(FilterHook::Input),
+        NF_INET_LOCAL_OUT => Ok(FilterHook::Output),
+        _ => return_errno_with_message!(Errno::EINVAL, "the hook is invalid"),
+    }
+}
+
+impl TryFrom<CNfRule> for FilterRule {
+    type Error = Error;
+
+    fn try_from(value: CNfRule) -> Result<Self> {
+        let verdict = match value.verdict {
+            NF_DROP => FilterVerdict::Drop,
+            NF_ACCEPT => FilterVerdict::Accept,
+            _ => return_errno_with_message!(Errno::EINVAL, "the verdict is invalid"),
+        };
+
+        let ip_version = match CSocketAddrFamily::try_from(value.family as i32) {
+            Ok(CSocketAddrFamily::AF_UNSPEC) => None,
+            Ok(CSocketAddrFamily::AF_INET) => Some(IpVersion::Ipv4),
+            Ok(CSocketAddrFamily::AF_INET6) => Some(IpVersion::Ipv6),
+            _ => return_errno_with_message!(
+                Errno::EAFNOSUPPORT,
+                "the address family is not supported"
+            ),
+        };
+
+        let port_from_c = |port: u16| match u16::from_be(port) {
+            0 => None,
+            port => Some(port),
+        };
+
+        Ok(Self {
+            hook: hook_from_c(value.hook)?,
+            ifindex: value.ifindex,
+            ip_version,
+            protocol: match value.protocol {
+                0 => None,
+                protocol => Some(IpProtocol::from(protocol)),
+            },
+            src: cidr_from_c(ip_version, &value.src_addr, value.src_prefix_len)?,
+            dst: cidr_from_c(ip_version, &value.dst_addr, value.dst_prefix_len)?,
+            src_port: port_from_c(value.src_port),
+            dst_port: port_from_c(value.dst_port),
+            verdict,
+        })
+    }
+}
+
+/// Converts the address and the prefix length to a subnet.
+///
+/// An IPv4 address occupies the first four bytes of the address.
+fn cidr_from_c(
+    ip_version: Option<IpVersion>,
+    addr: &[u8; 16],
+    prefix_len: u8,
+) -> Result<Option<IpCidr>> {
+    if prefix_len == 0 {
+        return Ok(None);
+    }
+
+    let cidr = match ip_version {
+        Some(IpVersion::Ipv4) if prefix_len <= 32 => {
+            let addr: [u8; 4] = addr[..4].try_into().unwrap();
+            IpCidr::Ipv4(Ipv4Cidr::new(Ipv4Address::from(addr), prefix_len))
+        }
+        Some(IpVersion::Ipv6) if prefix_len <= 128 => {
+            IpCidr::Ipv6(Ipv6Cidr::new(Ipv6Address::from(*addr), prefix_len))
+        }
+        None => return_errno_with_message!(
+            Errno::EINVAL,
+            "the address family must be specified to match the addresses"
+        ),
+        _ => return_errno_with_message!(Errno::EINVAL, "the prefix length is invalid"),
+    };
+
+    Ok(Some(cidr))
+}
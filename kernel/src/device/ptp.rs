@@ -0,0 +1,208 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! The `/dev/ptp0` device, which is the PTP hardware clock of KVM.
+//!
+//! The clock is the real time of the host, which is read with the clock pairing hypercall of
+//! kvm-clock. So the guest can synchronize its clocks with the host precisely, e.g., by
+//! `chronyd` with `refclock PHC /dev/ptp0`.
+//!
+//! Like Linux, the clock can be read with `clock_gettime(2)` via the dynamic clock ID of an
+//! opened file, and the offsets between the clock and the system clocks can be measured with
+//! the `PTP_SYS_OFFSET*` ioctls. The clock cannot be adjusted.
+//!
+//! Reference: <https://docs.kernel.org/driver-api/ptp.html>
+
+use core::time::Duration;
+
+use ostd::arch::kvmclock;
+
+use super::*;
+use crate::{
+    current_userspace,
+    events::IoEvents,
+    fs::{
+        file_handle::FileLike,
+        inode_handle::FileIo,
+        utils::{InodeType, IoctlCmd},
+    },
+    prelude::*,
+    process::signal::{PollHandle, Pollable},
+    time::{clocks::RealTimeClock, timekeeping, Clock},
+};
+
+/// The maximum number of samples of the `PTP_SYS_OFFSET` ioctl.
+const PTP_MAX_SAMPLES: usize = 25;
+
+/// Returns whether the PTP clock of KVM is available.
+pub(super) fn is_available() -> bool {
+    kvmclock::clock_pairing().is_some()
+}
+
+pub struct PtpDevice;
+
+impl Device for PtpDevice {
+    fn type_(&self) -> DeviceType {
+        DeviceType::CharDevice
+    }
+
+    fn id(&self) -> DeviceId {
+        ptp_device_id()
+    }
+
+    fn open(&self) -> Result<Option<Arc<dyn FileIo>>> {
+        Ok(Some(Arc::new(PtpFile)))
+    }
+}
+
+fn ptp_device_id() -> DeviceId {
+    // Linux allocates the major number dynamically, which is usually this value.
+    DeviceId::new(248, 0)
+}
+
+/// Reads the PTP clock that the file is opened from.
+pub fn read_clock_of(file: &dyn FileLike) -> Result<Duration> {
+    let metadata = file.metadata();
+    if metadata.type_ != InodeType::CharDevice || metadata.rdev != u64::from(ptp_device_id()) {
+        return_errno_with_message!(Errno::EINVAL, "the file is not a PTP clock");
+    }
+
+    read_clock()
+}
+
+fn read_clock() -> Result<Duration> {
+    kvmclock::clock_pairing()
+        .map(|pairing| pairing.realtime)
+        .ok_or_else(|| Error::with_message(Errno::EOPNOTSUPP, "the host does not pair clocks"))
+}
+
+/// An opened `/dev/ptp0` file.
+struct PtpFile;
+
+impl PtpFile {
+    fn get_caps(&self, arg: usize) -> Result<()> {
+        let caps = ptp_clock_caps {
+            cross_timestamping: 1,
+            ..Default::default()
+        };
+        current_userspace!().write_val(arg, &caps)?;
+        Ok(())
+    }
+
+    fn sys_offset(&self, arg: usize) -> Result<()> {
+        let mut offset = current_userspace!().read_val::<ptp_sys_offset>(arg)?;
+        let n_samples = offset.n_samples as usize;
+        if n_samples > PTP_MAX_SAMPLES {
+            return_errno_with_message!(Errno::EINVAL, "there are too many samples");
+        }
+
+        // The clock is read between two reads of the system clock in each sample.
+        let realtime_clock = RealTimeClock::get();
+        for i in 0..n_samples {
+            offset.ts[2 * i] = realtime_clock.read_time().into();
+            offset.ts[2 * i + 1] = read_clock()?.into();
+        }
+        offset.ts[2 * n_samples] = realtime_clock.read_time().into();
+
+        current_userspace!().write_val(arg, &offset)?;
+        Ok(())
+    }
+
+    fn sys_offset_precise(&self, arg: usize) -> Result<()> {
+        let pairing = kvmclock::clock_pairing().ok_or_else(|| {
+            Error::with_message(Errno::EOPNOTSUPP, "the host does not pair clocks")
+        })?;
+        // The pairing contains the TSC, from which the system clocks are calculated.
+        let instant = aster_time::default_clocksource()
+            .instant_at(pairing.tsc)
+            .ok_or_else(|| Error::with_message(Errno::EAGAIN, "the clocksource is updated"))?;
+        let snapshot = timekeeping::snapshot_at(Duration::new(instant.secs(), instant.nanos()));
+
+        let offset = ptp_sys_offset_precise {
+            device: pairing.realtime.into(),
+            sys_realtime: snapshot.realtime.into(),
+            sys_monoraw: snapshot.raw.into(),
+            rsv: [0; 4],
+        };
+        current_userspace!().write_val(arg, &offset)?;
+        Ok(())
+    }
+}
+
+impl Pollable for PtpFile {
+    fn poll(&self, mask: IoEvents, _poller: Option<&mut PollHandle>) -> IoEvents {
+        // There are no external timestamp events.
+        IoEvents::empty() & mask
+    }
+}
+
+impl FileIo for PtpFile {
+    fn read(&self, _writer: &mut VmWriter) -> Result<usize> {
+        return_errno_with_message!(Errno::EOPNOTSUPP, "external timestamps are not supported");
+    }
+
+    fn write(&self, _reader: &mut VmReader) -> Result<usize> {
+        return_errno_with_message!(Errno::EINVAL, "the PTP clock cannot be written");
+    }
+
+    fn ioctl(&self, cmd: IoctlCmd, arg: usize) -> Result<i32> {
+        match cmd {
+            IoctlCmd::PTP_CLOCK_GETCAPS | IoctlCmd::PTP_CLOCK_GETCAPS2 => self.get_caps(arg)?,
+            IoctlCmd::PTP_SYS_OFFSET | IoctlCmd::PTP_SYS_OFFSET2 => self.sys_offset(arg)?,
+            IoctlCmd::PTP_SYS_OFFSET_PRECISE | IoctlCmd::PTP_SYS_OFFSET_PRECISE2 => {
+                self.sys_offset_precise(arg)?
+            }
+            _ => return_errno_with_message!(Errno::ENOTTY, "the ioctl is not supported"),
+        }
+        Ok(0)
+    }
+}
+
+#[repr(C)]
+#[derive(Debug, Default, Clone, Copy, Pod)]
+struct ptp_clock_time {
+    sec: i64,
+    nsec: u32,
+    reserved: u32,
+}
+
+impl From<Duration> for ptp_clock_time {
+    fn from(duration: Duration) -> Self {
+        Self {
+            sec: duration.as_secs() as i64,
+            nsec: duration.subsec_nanos(),
+            reserved: 0,
+        }
+    }
+}
+
+#[repr(C)]
+#[derive(Debug, Default, Clone, Copy, Pod)]
+struct ptp_clock_caps {
+    max_adj: i32,
+    n_alarm: i32,
+    n_ext_ts: i32,
+    n_per_out: i32,
+    pps: i32,
+    n_pins: i32,
+    cross_timestamping: i32,
+    adjust_phase: i32,
+    max_phase_adj: i32,
+    rsv: [i32; 11],
+}
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Pod)]
+struct ptp_sys_offset {
+    n_samples: u32,
+    rsv: [u32; 3],
+    ts: [ptp_clock_time; 2 * PTP_MAX_SAMPLES + 1],
+}
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Pod)]
+struct ptp_sys_offset_precise {
+    device: ptp_clock_time,
+    sys_realtime: ptp_clock_time,
+    sys_monoraw: ptp_clock_time,
+    rsv: [u32; 4],
+}
@@ -11,7 +11,7 @@ use crate::{
     fs::{
         device::{Device, DeviceId, DeviceType},
         devpts::DevPts,
-        file_table::FdFlags,
+        file_table::{max_fds, FdFlags},
         fs_resolver::FsPath,
         inode_handle::FileIo,
         utils::{AccessMode, Inode, InodeMode, IoctlCmd},
@@ -208,7 +208,8 @@ impl FileIo for PtyMaster {
                     let fs_path = FsPath::try_from(slave_name.as_str())?;
 
                     let inode_handle = {
-                        let fs = posix_thread.fs().resolver().read();
+                        let fs_ref = thread_local.borrow_fs();
+                        let fs = fs_ref.resolver().read();
                         let flags = AccessMode::O_RDWR as u32;
                         let mode = (InodeMode::S_IRUSR | InodeMode::S_IWUSR).bits();
                         fs.open(&fs_path, flags, mode)?
@@ -217,10 +218,11 @@ impl FileIo for PtyMaster {
                 };
 
                 let fd = {
+                    let max_fds = max_fds(&posix_thread.process());
                     let file_table = thread_local.file_table().borrow();
                     let mut file_table_locked = file_table.write();
                     // TODO: deal with the O_CLOEXEC flag
-                    file_table_locked.insert(slave, FdFlags::empty())
+                    file_table_locked.insert(slave, FdFlags::empty(), max_fds)?
                 };
                 Ok(fd)
             }
@@ -241,7 +243,7 @@ impl FileIo for PtyMaster {
                         "the foreground process group does not exist"
                     );
                 };
-                let fg_pgid = foreground.pgid();
+                let fg_pgid = current!().pid_ns().local_id(foreground.pgid()).unwrap_or(0);
                 current_userspace!().write_val(arg, &fg_pgid)?;
                 Ok(0)
             }
@@ -251,7 +253,7 @@ impl FileIo for PtyMaster {
                     if pgid < 0 {
                         return_errno_with_message!(Errno::EINVAL, "negative pgid");
                     }
-                    pgid as u32
+                    current!().pid_ns().global_id(pgid as u32).unwrap_or(0)
                 };
 
                 self.set_foreground(&pgid)?;
@@ -297,7 +299,9 @@ impl Drop for PtyMaster {
 
 pub struct PtySlave {
     master: Weak<PtyMaster>,
-    job_control: JobControl,
+    /// The job control shared with the master, whose line discipline sends signals to the
+    /// foreground process group.
+    job_control: Arc<JobControl>,
     weak_self: Weak<Self>,
 }
 
@@ -305,7 +309,7 @@ impl PtySlave {
     pub fn new(master: &Arc<PtyMaster>) -> Arc<Self> {
         Arc::new_cyclic(|weak_ref| PtySlave {
             master: Arc::downgrade(master),
-            job_control: JobControl::new(),
+            job_control: master.job_control.clone(),
             weak_self: weak_ref.clone(),
         })
     }
@@ -348,16 +352,20 @@ impl Pollable for PtySlave {
 impl FileIo for PtySlave {
     fn read(&self, writer: &mut VmWriter) -> Result<usize> {
         let mut buf = vec![0u8; writer.avail()];
-        self.job_control.wait_until_in_foreground()?;
+        self.job_control.check_read()?;
         let read_len = self.master().output.read(&mut buf)?;
         writer.write_fallible(&mut buf.as_slice().into())?;
         Ok(read_len)
     }
 
     fn write(&self, reader: &mut VmReader) -> Result<usize> {
+        let master = self.master();
+        if master.output.termios().contains_tostop() {
+            self.job_control.check_write()?;
+        }
+
         let buf = reader.collect()?;
         let write_len = buf.len();
-        let master = self.master();
         for ch in buf {
             // do we need to add '\r' here?
             if ch == b'\n' {
@@ -372,11 +380,13 @@ impl FileIo for PtySlave {
 
     fn ioctl(&self, cmd: IoctlCmd, arg: usize) -> Result<i32> {
         match cmd {
-            IoctlCmd::TCGETS
-            | IoctlCmd::TCSETS
-            | IoctlCmd::TIOCGPTN
-            | IoctlCmd::TIOCGWINSZ
-            | IoctlCmd::TIOCSWINSZ => self.master().ioctl(cmd, arg),
+            IoctlCmd::TCSETS => {
+                self.job_control.check_write()?;
+                self.master().ioctl(cmd, arg)
+            }
+            IoctlCmd::TCGETS | IoctlCmd::TIOCGPTN | IoctlCmd::TIOCGWINSZ | IoctlCmd::TIOCSWINSZ => {
+                self.master().ioctl(cmd, arg)
+            }
             IoctlCmd::TIOCGPGRP => {
                 if !self.is_controlling_terminal() {
                     return_errno_with_message!(Errno::ENOTTY, "slave is not controlling terminal");
@@ -389,7 +399,7 @@ impl FileIo for PtySlave {
                     );
                 };
 
-                let fg_pgid = foreground.pgid();
+                let fg_pgid = current!().pid_ns().local_id(foreground.pgid()).unwrap_or(0);
                 current_userspace!().write_val(arg, &fg_pgid)?;
                 Ok(0)
             }
@@ -399,7 +409,7 @@ impl FileIo for PtySlave {
                     if pgid < 0 {
                         return_errno_with_message!(Errno::EINVAL, "negative pgid");
                     }
-                    pgid as u32
+                    current!().pid_ns().global_id(pgid as u32).unwrap_or(0)
                 };
 
                 self.set_foreground(&pgid)?;
@@ -1,27 +1,26 @@
 // SPDX-License-Identifier: MPL-2.0
 
-#![allow(unused_variables)]
-
 use crate::{
+    current_userspace,
     events::IoEvents,
     fs::{
         device::{Device, DeviceId, DeviceType},
         inode_handle::FileIo,
+        utils::IoctlCmd,
     },
     prelude::*,
-    process::signal::{PollHandle, Pollable},
-    util::random::getrandom,
+    process::{
+        credentials::capabilities::CapSet,
+        posix_thread::AsPosixThread,
+        signal::{PollHandle, Pollable},
+    },
+    util::random,
 };
 
+/// The `/dev/random` device, whose reads wait until the kernel random number generator is
+/// ready.
 pub struct Random;
 
-impl Random {
-    pub fn getrandom(buf: &mut [u8]) -> Result<usize> {
-        getrandom(buf)?;
-        Ok(buf.len())
-    }
-}
-
 impl Device for Random {
     fn type_(&self) -> DeviceType {
         DeviceType::CharDevice
@@ -38,21 +37,99 @@ impl Device for Random {
 }
 
 impl Pollable for Random {
-    fn poll(&self, mask: IoEvents, poller: Option<&mut PollHandle>) -> IoEvents {
-        let events = IoEvents::IN | IoEvents::OUT;
+    fn poll(&self, mask: IoEvents, _poller: Option<&mut PollHandle>) -> IoEvents {
+        let events = if random::is_ready() {
+            IoEvents::IN | IoEvents::OUT
+        } else {
+            IoEvents::OUT
+        };
         events & mask
     }
 }
 
 impl FileIo for Random {
     fn read(&self, writer: &mut VmWriter) -> Result<usize> {
-        let mut buf = vec![0; writer.avail()];
-        let size = Self::getrandom(buf.as_mut_slice());
-        writer.write_fallible(&mut buf.as_slice().into())?;
-        size
+        // TODO: Deal with nonblocking reads.
+        random::wait_until_ready()?;
+        random::getrandom_user(writer)
     }
 
     fn write(&self, reader: &mut VmReader) -> Result<usize> {
-        Ok(reader.remain())
+        write_random(reader)
     }
+
+    fn ioctl(&self, cmd: IoctlCmd, arg: usize) -> Result<i32> {
+        ioctl_random(cmd, arg)
+    }
+}
+
+/// Mixes the bytes from `reader` into the input pool without crediting any entropy.
+///
+/// Like Linux, anyone can write to `/dev/random` and `/dev/urandom`, since the written bytes
+/// cannot reduce the entropy of the pool.
+pub(super) fn write_random(reader: &mut VmReader) -> Result<usize> {
+    let len = reader.remain();
+    let mut buf = vec![0; len];
+    reader.read_fallible(&mut buf.as_mut_slice().into())?;
+    random::add_device_randomness(&buf);
+    Ok(len)
+}
+
+/// Handles the ioctls of `/dev/random` and `/dev/urandom`.
+pub(super) fn ioctl_random(cmd: IoctlCmd, arg: usize) -> Result<i32> {
+    if let IoctlCmd::RNDGETENTCNT = cmd {
+        current_userspace!().write_val(arg, &(random::entropy_avail() as i32))?;
+        return Ok(0);
+    }
+
+    let is_privileged = {
+        let current = current_thread!();
+        let credentials = current.as_posix_thread().unwrap().credentials();
+        credentials.effective_capset().contains(CapSet::SYS_ADMIN)
+    };
+    if !is_privileged {
+        return_errno_with_message!(Errno::EPERM, "the ioctl requires CAP_SYS_ADMIN");
+    }
+
+    match cmd {
+        IoctlCmd::RNDADDTOENTCNT => {
+            let bits = current_userspace!().read_val::<i32>(arg)?;
+            if bits < 0 {
+                return_errno_with_message!(Errno::EINVAL, "the entropy count is negative");
+            }
+            random::add_entropy(&[], bits as usize);
+        }
+        IoctlCmd::RNDADDENTROPY => {
+            let info = current_userspace!().read_val::<RandPoolInfo>(arg)?;
+            if info.entropy_count < 0 || info.buf_size < 0 {
+                return_errno_with_message!(Errno::EINVAL, "the entropy count or size is negative");
+            }
+            let mut buf = vec![0; info.buf_size as usize];
+            current_userspace!().read_bytes(
+                arg + size_of::<RandPoolInfo>(),
+                &mut VmWriter::from(buf.as_mut_slice()),
+            )?;
+            random::add_entropy(&buf, info.entropy_count as usize);
+        }
+        // Like Linux, the credited entropy cannot be cleared.
+        IoctlCmd::RNDZAPENTCNT | IoctlCmd::RNDCLEARPOOL => {}
+        IoctlCmd::RNDRESEEDCRNG => {
+            if !random::is_ready() {
+                return_errno_with_message!(Errno::EAGAIN, "the CRNG is not ready");
+            }
+            random::reseed();
+        }
+        _ => return_errno_with_message!(Errno::ENOTTY, "the ioctl is not supported"),
+    }
+
+    Ok(0)
+}
+
+/// The header of the argument of `RNDADDENTROPY` (`struct rand_pool_info`), which is followed
+/// by `buf_size` bytes.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Pod)]
+struct RandPoolInfo {
+    entropy_count: i32,
+    buf_size: i32,
 }
@@ -14,7 +14,7 @@ use crate::{
     events::IoEvents,
     prelude::*,
     process::signal::{
-        constants::{SIGINT, SIGQUIT},
+        constants::{SIGINT, SIGQUIT, SIGTSTP},
         signals::kernel::KernelSignal,
         PollHandle, Pollable, Pollee,
     },
@@ -134,7 +134,11 @@ impl LineDiscipline {
 
         if self.may_send_signal(&termios, ch) {
             submit_work_item(self.work_item.clone(), WorkPriority::High);
-            // CBREAK mode may require the character to be outputted, so just go ahead.
+            // The character that generates the signal is echoed, but it is not passed to readers.
+            if termios.contain_echo() {
+                self.output_char(ch, &termios, echo_callback);
+            }
+            return;
         }
 
         // Typically, a tty in raw mode does not echo. But the tty can also be in a CBREAK mode,
@@ -183,13 +187,16 @@ impl LineDiscipline {
     }
 
     fn may_send_signal(&self, termios: &KernelTermios, ch: u8) -> bool {
-        if !termios.is_canonical_mode() || !termios.contains_isig() {
+        // Unlike other special characters, the signal characters are also recognized in the
+        // non-canonical mode.
+        if !termios.contains_isig() {
             return false;
         }
 
         let signal = match ch {
             ch if ch == *termios.get_special_char(CC_C_CHAR::VINTR) => KernelSignal::new(SIGINT),
             ch if ch == *termios.get_special_char(CC_C_CHAR::VQUIT) => KernelSignal::new(SIGQUIT),
+            ch if ch == *termios.get_special_char(CC_C_CHAR::VSUSP) => KernelSignal::new(SIGTSTP),
             _ => return false,
         };
 
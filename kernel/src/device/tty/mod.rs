@@ -81,13 +81,17 @@ impl Pollable for Tty {
 impl FileIo for Tty {
     fn read(&self, writer: &mut VmWriter) -> Result<usize> {
         let mut buf = vec![0; writer.avail()];
-        self.job_control.wait_until_in_foreground()?;
+        self.job_control.check_read()?;
         let read_len = self.ldisc.read(buf.as_mut_slice())?;
         writer.write_fallible(&mut buf.as_slice().into())?;
         Ok(read_len)
     }
 
     fn write(&self, reader: &mut VmReader) -> Result<usize> {
+        if self.ldisc.termios().contains_tostop() {
+            self.job_control.check_write()?;
+        }
+
         let buf = reader.collect()?;
         if let Ok(content) = alloc::str::from_utf8(&buf) {
             print!("{content}");
@@ -107,10 +111,14 @@ impl FileIo for Tty {
                 Ok(0)
             }
             IoctlCmd::TIOCGPGRP => {
+                if !self.is_controlling_terminal() {
+                    return_errno_with_message!(Errno::ENOTTY, "tty is not controlling terminal");
+                }
+
                 let Some(foreground) = self.foreground() else {
                     return_errno_with_message!(Errno::ESRCH, "No fg process group")
                 };
-                let fg_pgid = foreground.pgid();
+                let fg_pgid = current!().pid_ns().local_id(foreground.pgid()).unwrap_or(0);
                 debug!("fg_pgid = {}", fg_pgid);
                 current_userspace!().write_val(arg, &fg_pgid)?;
                 Ok(0)
@@ -122,7 +130,7 @@ impl FileIo for Tty {
                     if pgid < 0 {
                         return_errno_with_message!(Errno::EINVAL, "negative pgid");
                     }
-                    pgid as u32
+                    current!().pid_ns().global_id(pgid as u32).unwrap_or(0)
                 };
 
                 self.set_foreground(&pgid)?;
@@ -130,12 +138,14 @@ impl FileIo for Tty {
             }
             IoctlCmd::TCSETS => {
                 // Set terminal attributes
+                self.job_control.check_write()?;
                 let termios = current_userspace!().read_val(arg)?;
                 debug!("set termios = {:?}", termios);
                 self.ldisc.set_termios(termios);
                 Ok(0)
             }
             IoctlCmd::TCSETSW => {
+                self.job_control.check_write()?;
                 let termios = current_userspace!().read_val(arg)?;
                 debug!("set termios = {:?}", termios);
                 self.ldisc.set_termios(termios);
@@ -143,6 +153,7 @@ impl FileIo for Tty {
                 Ok(0)
             }
             IoctlCmd::TCSETSF => {
+                self.job_control.check_write()?;
                 let termios = current_userspace!().read_val(arg)?;
                 debug!("set termios = {:?}", termios);
                 self.ldisc.set_termios(termios);
@@ -164,6 +175,10 @@ impl FileIo for Tty {
                 self.set_current_session()?;
                 Ok(0)
             }
+            IoctlCmd::TIOCNOTTY => {
+                self.release_current_session()?;
+                Ok(0)
+            }
             _ => todo!(),
         }
     }
@@ -226,7 +241,7 @@ pub fn open_ntty_as_controlling_terminal(process: &Process) -> Result<()> {
         Ok(tty.clone())
     })?;
 
-    tty.job_control.set_foreground(Some(&process_group))?;
+    tty.job_control.set_foreground(&process_group)?;
 
     Ok(())
 }
@@ -286,6 +286,11 @@ impl KernelTermios {
     pub fn contains_iexten(&self) -> bool {
         self.c_lflags.contains(C_LFLAGS::IEXTEN)
     }
+
+    /// TOSTOP means background processes should be stopped when writing to the terminal
+    pub fn contains_tostop(&self) -> bool {
+        self.c_lflags.contains(C_LFLAGS::TOSTOP)
+    }
 }
 
 const fn control_character(c: char) -> u8 {
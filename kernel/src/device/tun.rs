@@ -0,0 +1,283 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! The `/dev/net/tun` device, which creates TUN/TAP ifaces.
+//!
+//! Each opened file is attached to a new iface by the `TUNSETIFF` ioctl. After that, reading the
+//! file receives the packets sent by the iface, and writing the file delivers packets to the
+//! iface. The iface is removed when the file is closed.
+
+use super::*;
+use crate::{
+    current_userspace,
+    events::IoEvents,
+    fs::{inode_handle::FileIo, utils::IoctlCmd},
+    net::iface::{TunIface, TunKind},
+    prelude::*,
+    process::{
+        credentials::capabilities::CapSet,
+        posix_thread::AsPosixThread,
+        signal::{PollHandle, Pollable, Pollee},
+    },
+};
+
+pub struct TunDevice;
+
+impl Device for TunDevice {
+    fn type_(&self) -> DeviceType {
+        DeviceType::CharDevice
+    }
+
+    fn id(&self) -> DeviceId {
+        // Same value as Linux
+        DeviceId::new(10, 200)
+    }
+
+    fn open(&self) -> Result<Option<Arc<dyn FileIo>>> {
+        Ok(Some(Arc::new(TunFile::new())))
+    }
+}
+
+/// An opened `/dev/net/tun` file.
+struct TunFile {
+    attached: Mutex<Option<Attached>>,
+    pollee: Pollee,
+}
+
+struct Attached {
+    iface: Arc<TunIface>,
+    flags: TunFlags,
+}
+
+bitflags! {
+    /// The flags of the `TUNSETIFF` ioctl.
+    struct TunFlags: u16 {
+        const IFF_TUN = 0x0001;
+        const IFF_TAP = 0x0002;
+        const IFF_NO_PI = 0x1000;
+        /// This flag is obsolete and ignored, like in Linux.
+        const IFF_ONE_QUEUE = 0x2000;
+    }
+}
+
+/// The interface request of the `TUNSETIFF` ioctl.
+///
+/// This corresponds to the `ifreq` structure with the `ifr_flags` member.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Pod)]
+struct CIfreq {
+    name: [u8; 16],
+    flags: u16,
+    _pad: [u8; 22],
+}
+
+/// The packet information that precedes each packet unless `IFF_NO_PI` is specified.
+///
+/// This corresponds to the `tun_pi` structure in Linux.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Pod)]
+struct CTunPi {
+    flags: u16,
+    /// The protocol of the packet, in network byte order.
+    proto: u16,
+}
+
+const ETH_P_IP: u16 = 0x0800;
+const ETH_P_IPV6: u16 = 0x86DD;
+
+impl TunFile {
+    fn new() -> Self {
+        Self {
+            attached: Mutex::new(None),
+            pollee: Pollee::new(),
+        }
+    }
+
+    fn set_iff(&self, arg: usize) -> Result<()> {
+        check_net_admin()?;
+
+        let mut c_ifreq: CIfreq = current_userspace!().read_val(arg)?;
+
+        let Some(flags) = TunFlags::from_bits(c_ifreq.flags) else {
+            return_errno_with_message!(Errno::EINVAL, "the flags are not supported");
+        };
+        let kind = match flags & (TunFlags::IFF_TUN | TunFlags::IFF_TAP) {
+            TunFlags::IFF_TUN => TunKind::Tun,
+            TunFlags::IFF_TAP => TunKind::Tap,
+            _ => return_errno_with_message!(
+                Errno::EINVAL,
+                "exactly one of IFF_TUN and IFF_TAP must be specified"
+            ),
+        };
+
+        let name_len = c_ifreq
+            .name
+            .iter()
+            .position(|byte| *byte == 0)
+            .unwrap_or(c_ifreq.name.len());
+        let Ok(name) = core::str::from_utf8(&c_ifreq.name[..name_len]) else {
+            return_errno_with_message!(Errno::EINVAL, "the iface name is invalid");
+        };
+
+        let mut attached = self.attached.lock();
+        if attached.is_some() {
+            return_errno_with_message!(Errno::EEXIST, "the file is already attached to an iface");
+        }
+
+        let iface = TunIface::new(name, kind, self.pollee.clone())?;
+
+        c_ifreq.name = [0; 16];
+        c_ifreq.name[..iface.name().len()].copy_from_slice(iface.name().as_bytes());
+        current_userspace!().write_val(arg, &c_ifreq)?;
+
+        *attached = Some(Attached { iface, flags });
+
+        Ok(())
+    }
+
+    fn get_iff(&self, arg: usize) -> Result<()> {
+        let attached = self.attached.lock();
+        let Some(attached) = attached.as_ref() else {
+            return_errno_with_message!(Errno::EBADFD, "the file is not attached to an iface");
+        };
+
+        let mut c_ifreq = CIfreq::new_zeroed();
+        c_ifreq.name[..attached.iface.name().len()]
+            .copy_from_slice(attached.iface.name().as_bytes());
+        c_ifreq.flags = attached.flags.bits();
+        current_userspace!().write_val(arg, &c_ifreq)?;
+
+        Ok(())
+    }
+
+    fn attached_iface(&self) -> Result<(Arc<TunIface>, TunFlags)> {
+        let attached = self.attached.lock();
+        let Some(attached) = attached.as_ref() else {
+            return_errno_with_message!(Errno::EBADFD, "the file is not attached to an iface");
+        };
+
+        Ok((attached.iface.clone(), attached.flags))
+    }
+
+    fn try_read(&self, iface: &TunIface, flags: TunFlags, writer: &mut VmWriter) -> Result<usize> {
+        let Some(packet) = iface.pop_packet() else {
+            return_errno_with_message!(Errno::EAGAIN, "no packets are available");
+        };
+
+        let mut read_len = 0;
+
+        if !flags.contains(TunFlags::IFF_NO_PI) {
+            let pi = CTunPi {
+                flags: 0,
+                proto: packet_proto(iface.kind(), &packet).to_be(),
+            };
+            writer.write_val(&pi)?;
+            read_len += size_of::<CTunPi>();
+        }
+
+        // Like Linux, the packet is truncated if the buffer is too small.
+        let packet_len = packet.len().min(writer.avail());
+        writer.write_fallible(&mut packet[..packet_len].into())?;
+        read_len += packet_len;
+
+        Ok(read_len)
+    }
+
+    fn check_io_events(&self) -> IoEvents {
+        let attached = self.attached.lock();
+
+        match attached.as_ref() {
+            Some(attached) if attached.iface.has_packet() => IoEvents::IN | IoEvents::OUT,
+            _ => IoEvents::OUT,
+        }
+    }
+}
+
+/// Returns the protocol of the packet that is reported in the packet information.
+fn packet_proto(kind: TunKind, packet: &[u8]) -> u16 {
+    match kind {
+        TunKind::Tun => match packet.first().map(|byte| byte >> 4) {
+            Some(4) => ETH_P_IP,
+            Some(6) => ETH_P_IPV6,
+            _ => 0,
+        },
+        TunKind::Tap => match packet.get(12..14) {
+            Some(ether_type) => u16::from_be_bytes([ether_type[0], ether_type[1]]),
+            None => 0,
+        },
+    }
+}
+
+impl Pollable for TunFile {
+    fn poll(&self, mask: IoEvents, poller: Option<&mut PollHandle>) -> IoEvents {
+        self.pollee
+            .poll_with(mask, poller, || self.check_io_events())
+    }
+}
+
+impl FileIo for TunFile {
+    fn read(&self, writer: &mut VmWriter) -> Result<usize> {
+        let (iface, flags) = self.attached_iface()?;
+
+        // TODO: Deal with nonblocking reads.
+        self.wait_events(IoEvents::IN, None, || self.try_read(&iface, flags, writer))
+    }
+
+    fn write(&self, reader: &mut VmReader) -> Result<usize> {
+        let (iface, flags) = self.attached_iface()?;
+
+        let mut write_len = 0;
+
+        if !flags.contains(TunFlags::IFF_NO_PI) {
+            if reader.remain() < size_of::<CTunPi>() {
+                return_errno_with_message!(Errno::EINVAL, "the packet information is incomplete");
+            }
+            // The protocol is determined by the packet itself, so the packet information is
+            // ignored.
+            let _pi: CTunPi = reader.read_val()?;
+            write_len += size_of::<CTunPi>();
+        }
+
+        if reader.remain() == 0 || reader.remain() > iface.max_packet_len() {
+            return_errno_with_message!(Errno::EINVAL, "the packet length is invalid");
+        }
+
+        let packet = reader.collect()?;
+        write_len += packet.len();
+
+        iface.push_packet(packet)?;
+
+        Ok(write_len)
+    }
+
+    fn ioctl(&self, cmd: IoctlCmd, arg: usize) -> Result<i32> {
+        match cmd {
+            IoctlCmd::TUNSETIFF => {
+                self.set_iff(arg)?;
+                Ok(0)
+            }
+            IoctlCmd::TUNGETIFF => {
+                self.get_iff(arg)?;
+                Ok(0)
+            }
+            _ => return_errno_with_message!(Errno::ENOTTY, "the ioctl is not supported"),
+        }
+    }
+}
+
+fn check_net_admin() -> Result<()> {
+    let current = current_thread!();
+    let posix_thread = current.as_posix_thread().unwrap();
+
+    if !posix_thread
+        .credentials()
+        .effective_capset()
+        .contains(CapSet::NET_ADMIN)
+    {
+        return_errno_with_message!(
+            Errno::EPERM,
+            "creating TUN/TAP ifaces requires the CAP_NET_ADMIN capability"
+        );
+    }
+
+    Ok(())
+}
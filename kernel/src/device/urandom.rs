@@ -1,27 +1,22 @@
 // SPDX-License-Identifier: MPL-2.0
 
-#![allow(unused_variables)]
-
+use super::random::{ioctl_random, write_random};
 use crate::{
     events::IoEvents,
     fs::{
         device::{Device, DeviceId, DeviceType},
         inode_handle::FileIo,
+        utils::IoctlCmd,
     },
     prelude::*,
     process::signal::{PollHandle, Pollable},
-    util::random::getrandom,
+    util::random,
 };
 
+/// The `/dev/urandom` device, whose reads never wait, even if the kernel random number
+/// generator is not ready.
 pub struct Urandom;
 
-impl Urandom {
-    pub fn getrandom(buf: &mut [u8]) -> Result<usize> {
-        getrandom(buf)?;
-        Ok(buf.len())
-    }
-}
-
 impl Device for Urandom {
     fn type_(&self) -> DeviceType {
         DeviceType::CharDevice
@@ -38,7 +33,7 @@ impl Device for Urandom {
 }
 
 impl Pollable for Urandom {
-    fn poll(&self, mask: IoEvents, poller: Option<&mut PollHandle>) -> IoEvents {
+    fn poll(&self, mask: IoEvents, _poller: Option<&mut PollHandle>) -> IoEvents {
         let events = IoEvents::IN | IoEvents::OUT;
         events & mask
     }
@@ -46,13 +41,14 @@ impl Pollable for Urandom {
 
 impl FileIo for Urandom {
     fn read(&self, writer: &mut VmWriter) -> Result<usize> {
-        let mut buf = vec![0; writer.avail()];
-        let size = Self::getrandom(buf.as_mut_slice());
-        writer.write_fallible(&mut buf.as_slice().into())?;
-        size
+        random::getrandom_user(writer)
     }
 
     fn write(&self, reader: &mut VmReader) -> Result<usize> {
-        Ok(reader.remain())
+        write_random(reader)
+    }
+
+    fn ioctl(&self, cmd: IoctlCmd, arg: usize) -> Result<i32> {
+        ioctl_random(cmd, arg)
     }
 }
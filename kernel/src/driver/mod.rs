@@ -2,7 +2,11 @@
 
 use log::info;
 
+mod power_button;
+
 pub fn init() {
+    power_button::init();
+
     // print all the input device to make sure input crate will compile
     for (name, _) in aster_input::all_devices() {
         info!("Found Input device, name:{}", name);
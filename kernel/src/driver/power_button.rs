@@ -0,0 +1,82 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! The power button of the machine.
+//!
+//! A press of the power button is reported as the key events of `KEY_POWER` by the input device
+//! named `power_button`. Since the input events are not available to the user space yet, the init
+//! process is also notified with `SIGPWR`, so that it can shut down the system gracefully (e.g.,
+//! when QEMU's `system_powerdown` command is issued).
+
+use core::fmt::Debug;
+
+use aster_input::{
+    key::{Key, KeyStatus},
+    InputDevice, InputEvent,
+};
+use ostd::sync::LocalIrqDisabled;
+
+use crate::{
+    prelude::*,
+    process::{
+        namespace::PidNamespace,
+        process_table,
+        signal::{constants::SIGPWR, signals::kernel::KernelSignal},
+    },
+    thread::work_queue::{submit_work_item, work_item::WorkItem, WorkPriority},
+};
+
+const DEVICE_NAME: &str = "power_button";
+
+type InputCallback = &'static (dyn Fn(InputEvent) + Send + Sync);
+
+struct PowerButton {
+    callbacks: RwLock<Vec<InputCallback>, LocalIrqDisabled>,
+    notify_init_work: Arc<WorkItem>,
+}
+
+pub(super) fn init() {
+    let power_button = Arc::new(PowerButton {
+        callbacks: RwLock::new(Vec::new()),
+        // Sending signals may sleep, so it is deferred to the work queue.
+        notify_init_work: WorkItem::new(Box::new(notify_init_process)),
+    });
+
+    aster_input::register_device(DEVICE_NAME.to_string(), power_button.clone());
+    ostd::power::register_power_button_callback(move || power_button.handle_press());
+}
+
+impl PowerButton {
+    fn handle_press(&self) {
+        for callback in self.callbacks.read().iter() {
+            callback(InputEvent::KeyBoard(Key::Power, KeyStatus::Pressed));
+            callback(InputEvent::KeyBoard(Key::Power, KeyStatus::Released));
+        }
+
+        submit_work_item(self.notify_init_work.clone(), WorkPriority::High);
+    }
+}
+
+fn notify_init_process() {
+    let Some(init_pid) = PidNamespace::get_init_singleton().init_process_id() else {
+        return;
+    };
+    let Some(init_process) = process_table::get_process(init_pid) else {
+        return;
+    };
+
+    init_process.enqueue_signal(KernelSignal::new(SIGPWR));
+}
+
+impl InputDevice for PowerButton {
+    fn register_callbacks(&self, function: &'static (dyn Fn(InputEvent) + Send + Sync)) {
+        self.callbacks.write().push(function);
+    }
+}
+
+impl Debug for PowerButton {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("PowerButton")
+            .field("num_callbacks", &self.callbacks.read().len())
+            .finish()
+    }
+}
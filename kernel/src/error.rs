@@ -246,6 +246,25 @@ impl From<aster_block::bio::BioStatus> for Error {
     }
 }
 
+impl From<aster_block::verity::VerityError> for Error {
+    fn from(error: aster_block::verity::VerityError) -> Self {
+        match error {
+            aster_block::verity::VerityError::IoError => {
+                Error::with_message(Errno::EIO, "I/O operation fails")
+            }
+            aster_block::verity::VerityError::InvalidSuperblock => {
+                Error::with_message(Errno::EINVAL, "The verity superblock is invalid")
+            }
+            aster_block::verity::VerityError::NotSupported => {
+                Error::with_message(Errno::EOPNOTSUPP, "The verity parameters are not supported")
+            }
+            aster_block::verity::VerityError::Corrupted => {
+                Error::with_message(Errno::EIO, "The block does not match the hash tree")
+            }
+        }
+    }
+}
+
 impl From<core::str::Utf8Error> for Error {
     fn from(_: core::str::Utf8Error) -> Self {
         Error::with_message(Errno::EINVAL, "Invalid utf-8 string")
@@ -27,8 +27,20 @@ impl Events for () {}
 /// the blanket implementations the `EventsFilter` trait.
 /// By using `Option<F: EventsFilter>`, we can decide, on a per-observer basis,
 /// if an observer needs an event filter.
+///
+/// # Exclusive observers
+///
+/// An event filter can also mark its observer as exclusive. When events happen, a `Subject`
+/// notifies all the non-exclusive observers, but stops notifying the exclusive observers once
+/// one of them has taken care of the events (see `Observer::on_exclusive_events`). This avoids
+/// the "thundering herd" problem if many observers are waiting for the same events.
 pub trait EventsFilter<E: Events>: Send + Sync + 'static {
     fn filter(&self, event: &E) -> bool;
+
+    /// Returns whether the observer should be notified exclusively.
+    fn is_exclusive(&self) -> bool {
+        false
+    }
 }
 
 impl<E: Events> EventsFilter<E> for () {
@@ -41,4 +53,8 @@ impl<E: Events, F: EventsFilter<E>> EventsFilter<E> for Option<F> {
     fn filter(&self, events: &E) -> bool {
         self.as_ref().map_or(true, |f| f.filter(events))
     }
+
+    fn is_exclusive(&self) -> bool {
+        self.as_ref().is_some_and(|f| f.is_exclusive())
+    }
 }
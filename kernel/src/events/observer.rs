@@ -32,6 +32,16 @@ use super::Events;
 pub trait Observer<E: Events>: Send + Sync {
     /// Notify the observer that some interesting events happen.
     fn on_events(&self, events: &E);
+
+    /// Notify the observer, which is registered as an exclusive one, that some interesting
+    /// events happen.
+    ///
+    /// The return value indicates whether the events have been taken care of (e.g., some waiter
+    /// has been woken up). If so, the remaining exclusive observers will not be notified.
+    fn on_exclusive_events(&self, events: &E) -> bool {
+        self.on_events(events);
+        true
+    }
 }
 
 impl<E: Events> Observer<E> for () {
@@ -14,6 +14,8 @@ pub struct Subject<E: Events, F: EventsFilter<E> = ()> {
     observers: SpinLock<BTreeMap<KeyableWeak<dyn Observer<E>>, F>, LocalIrqDisabled>,
     // To reduce lock contentions, we maintain a counter for the size of the table
     num_observers: AtomicUsize,
+    // A counter to choose which exclusive observer is notified, in a round-robin manner.
+    next_exclusive: AtomicUsize,
 }
 
 impl<E: Events, F: EventsFilter<E>> Subject<E, F> {
@@ -21,6 +23,7 @@ impl<E: Events, F: EventsFilter<E>> Subject<E, F> {
         Self {
             observers: SpinLock::new(BTreeMap::new()),
             num_observers: AtomicUsize::new(0),
+            next_exclusive: AtomicUsize::new(0),
         }
     }
 
@@ -63,9 +66,16 @@ impl<E: Events, F: EventsFilter<E>> Subject<E, F> {
         observer
     }
 
+    /// Returns whether there are any registered observers.
+    pub fn has_observers(&self) -> bool {
+        self.num_observers.load(Ordering::Relaxed) > 0
+    }
+
     /// Notify events to all registered observers.
     ///
-    /// It will remove the observers which have been freed.
+    /// If some of the interested observers are exclusive, they are notified one by one until one
+    /// of them takes care of the events (see [`EventsFilter::is_exclusive`]). It will remove the
+    /// observers which have been freed.
     pub fn notify_observers(&self, events: &E) {
         // Fast path.
         //
@@ -78,13 +88,18 @@ impl<E: Events, F: EventsFilter<E>> Subject<E, F> {
 
         // Slow path: broadcast the new events to all observers.
         let mut active_observers = Vec::new();
+        let mut exclusive_observers = Vec::new();
         let mut num_freed = 0;
         let mut observers = self.observers.lock();
         observers.retain(|observer, filter| {
             if let Some(observer) = observer.upgrade() {
-                if filter.filter(events) {
+                if !filter.filter(events) {
+                    // The observer is not interested in the events.
+                } else if filter.is_exclusive() {
+                    exclusive_observers.push(observer);
+                } else {
                     // XXX: Mind the performance impact when there comes many active observers
-                    active_observers.push(observer.clone());
+                    active_observers.push(observer);
                 }
                 true
             } else {
@@ -100,6 +115,17 @@ impl<E: Events, F: EventsFilter<E>> Subject<E, F> {
         for observer in active_observers {
             observer.on_events(events);
         }
+
+        if exclusive_observers.is_empty() {
+            return;
+        }
+        let start = self.next_exclusive.fetch_add(1, Ordering::Relaxed);
+        let len = exclusive_observers.len();
+        for i in 0..len {
+            if exclusive_observers[(start + i) % len].on_exclusive_events(events) {
+                break;
+            }
+        }
     }
 }
 
@@ -0,0 +1,462 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! The binfmt_misc filesystem, which manages the miscellaneous binary formats.
+//!
+//! The filesystem is usually mounted at `/proc/sys/fs/binfmt_misc`. The root directory contains
+//! the following files:
+//! - `register`: Writing a registration string registers a new format. See
+//!   [`crate::process::binfmt_misc`] for the syntax.
+//! - `status`: Reading it shows whether binfmt_misc is enabled. Writing `0` or `1` disables or
+//!   enables binfmt_misc, and writing `-1` removes all the formats.
+//! - `<name>`: Each format has a file of its name. Reading it shows the format. Writing `0` or
+//!   `1` disables or enables the format, and writing `-1` removes the format.
+
+use core::{
+    sync::atomic::{AtomicU64, Ordering},
+    time::Duration,
+};
+
+use ostd::task::Task;
+use spin::Once;
+
+use crate::{
+    fs::utils::{
+        DirentVisitor, FileSystem, FsFlags, Inode, InodeMode, InodeType, Metadata, SuperBlock,
+        NAME_MAX,
+    },
+    prelude::*,
+    process::{
+        binfmt_misc::{self, BinfmtEntry},
+        posix_thread::AsThreadLocal,
+        Gid, Uid,
+    },
+};
+
+const BINFMTFS_MAGIC: u64 = 0x4249_4e4d;
+const BLOCK_SIZE: usize = 4096;
+const ROOT_INO: u64 = 1;
+
+/// The binfmt_misc filesystem.
+///
+/// All the mounted instances share the same formats.
+pub struct BinfmtMiscFs {
+    sb: SuperBlock,
+    root: Arc<BinfmtMiscDir>,
+}
+
+impl BinfmtMiscFs {
+    /// Returns the binfmt_misc filesystem.
+    pub fn singleton() -> &'static Arc<BinfmtMiscFs> {
+        static SINGLETON: Once<Arc<BinfmtMiscFs>> = Once::new();
+
+        SINGLETON.call_once(|| {
+            Arc::new_cyclic(|weak_fs| Self {
+                sb: SuperBlock::new(BINFMTFS_MAGIC, BLOCK_SIZE, NAME_MAX),
+                root: BinfmtMiscDir::new(weak_fs.clone()),
+            })
+        })
+    }
+}
+
+fn alloc_ino() -> u64 {
+    static NEXT_INO: AtomicU64 = AtomicU64::new(ROOT_INO);
+
+    NEXT_INO.fetch_add(1, Ordering::Relaxed)
+}
+
+impl FileSystem for BinfmtMiscFs {
+    fn sync(&self) -> Result<()> {
+        Ok(())
+    }
+
+    fn root_inode(&self) -> Arc<dyn Inode> {
+        self.root.clone()
+    }
+
+    fn sb(&self) -> SuperBlock {
+        self.sb.clone()
+    }
+
+    fn flags(&self) -> FsFlags {
+        FsFlags::empty()
+    }
+
+    fn name(&self) -> &'static str {
+        "binfmt_misc"
+    }
+}
+
+/// The root directory of the filesystem.
+struct BinfmtMiscDir {
+    register: Arc<BinfmtMiscFile>,
+    status: Arc<BinfmtMiscFile>,
+    /// The files of the formats that have been looked up, indexed by their names.
+    entry_files: Mutex<BTreeMap<String, Arc<BinfmtMiscFile>>>,
+    metadata: RwLock<Metadata>,
+    this: Weak<BinfmtMiscDir>,
+    fs: Weak<BinfmtMiscFs>,
+}
+
+impl BinfmtMiscDir {
+    fn new(fs: Weak<BinfmtMiscFs>) -> Arc<Self> {
+        let ino = alloc_ino();
+        Arc::new_cyclic(|this| Self {
+            register: BinfmtMiscFile::new(FileKind::Register, fs.clone()),
+            status: BinfmtMiscFile::new(FileKind::Status, fs.clone()),
+            entry_files: Mutex::new(BTreeMap::new()),
+            metadata: RwLock::new(Metadata::new_dir(
+                ino,
+                InodeMode::from_bits_truncate(0o755),
+                BLOCK_SIZE,
+            )),
+            this: this.clone(),
+            fs,
+        })
+    }
+
+    /// Returns the file of the format.
+    ///
+    /// The file is reused as long as the format is not replaced, so that its inode number is
+    /// stable.
+    fn entry_file(&self, entry: Arc<BinfmtEntry>) -> Arc<BinfmtMiscFile> {
+        let mut entry_files = self.entry_files.lock();
+        entry_files.retain(|name, file| {
+            binfmt_misc::lookup(name).is_some_and(|registered| file.is_of_entry(&registered))
+        });
+        entry_files
+            .entry(entry.name().to_string())
+            .or_insert_with(|| BinfmtMiscFile::new(FileKind::Entry(entry), self.fs.clone()))
+            .clone()
+    }
+
+    fn this(&self) -> Arc<BinfmtMiscDir> {
+        self.this.upgrade().unwrap()
+    }
+}
+
+impl Inode for BinfmtMiscDir {
+    fn size(&self) -> usize {
+        0
+    }
+
+    fn resize(&self, _new_size: usize) -> Result<()> {
+        Err(Error::new(Errno::EISDIR))
+    }
+
+    fn metadata(&self) -> Metadata {
+        *self.metadata.read()
+    }
+
+    fn ino(&self) -> u64 {
+        self.metadata.read().ino
+    }
+
+    fn type_(&self) -> InodeType {
+        InodeType::Dir
+    }
+
+    fn mode(&self) -> Result<InodeMode> {
+        Ok(self.metadata.read().mode)
+    }
+
+    fn set_mode(&self, mode: InodeMode) -> Result<()> {
+        self.metadata.write().mode = mode;
+        Ok(())
+    }
+
+    fn owner(&self) -> Result<Uid> {
+        Ok(self.metadata.read().uid)
+    }
+
+    fn set_owner(&self, uid: Uid) -> Result<()> {
+        self.metadata.write().uid = uid;
+        Ok(())
+    }
+
+    fn group(&self) -> Result<Gid> {
+        Ok(self.metadata.read().gid)
+    }
+
+    fn set_group(&self, gid: Gid) -> Result<()> {
+        self.metadata.write().gid = gid;
+        Ok(())
+    }
+
+    fn atime(&self) -> Duration {
+        self.metadata.read().atime
+    }
+
+    fn set_atime(&self, time: Duration) {
+        self.metadata.write().atime = time;
+    }
+
+    fn mtime(&self) -> Duration {
+        self.metadata.read().mtime
+    }
+
+    fn set_mtime(&self, time: Duration) {
+        self.metadata.write().mtime = time;
+    }
+
+    fn ctime(&self) -> Duration {
+        self.metadata.read().ctime
+    }
+
+    fn set_ctime(&self, time: Duration) {
+        self.metadata.write().ctime = time;
+    }
+
+    fn create(&self, _name: &str, _type_: InodeType, _mode: InodeMode) -> Result<Arc<dyn Inode>> {
+        return_errno_with_message!(
+            Errno::EPERM,
+            "the formats can only be added by registration"
+        );
+    }
+
+    fn readdir_at(&self, offset: usize, visitor: &mut dyn DirentVisitor) -> Result<usize> {
+        let mut entries: Vec<(String, u64, InodeType)> = vec![
+            (String::from("."), self.ino(), InodeType::Dir),
+            (String::from(".."), self.ino(), InodeType::Dir),
+            (
+                String::from("register"),
+                self.register.ino(),
+                InodeType::File,
+            ),
+            (String::from("status"), self.status.ino(), InodeType::File),
+        ];
+        entries.extend(binfmt_misc::entries().into_iter().map(|entry| {
+            let name = entry.name().to_string();
+            (name, self.entry_file(entry).ino(), InodeType::File)
+        }));
+
+        let mut iterate_offset = offset;
+        for (name, ino, type_) in entries.iter().skip(offset) {
+            if let Err(err) = visitor.visit(name, *ino, *type_, iterate_offset) {
+                if iterate_offset == offset {
+                    return Err(err);
+                }
+                break;
+            }
+            iterate_offset += 1;
+        }
+
+        Ok(iterate_offset - offset)
+    }
+
+    fn unlink(&self, _name: &str) -> Result<()> {
+        return_errno_with_message!(
+            Errno::EPERM,
+            "the formats can only be removed by writing -1"
+        );
+    }
+
+    fn lookup(&self, name: &str) -> Result<Arc<dyn Inode>> {
+        match name {
+            "." | ".." => Ok(self.this()),
+            "register" => Ok(self.register.clone()),
+            "status" => Ok(self.status.clone()),
+            _ => {
+                let entry = binfmt_misc::lookup(name).ok_or_else(|| {
+                    Error::with_message(Errno::ENOENT, "the format does not exist")
+                })?;
+                Ok(self.entry_file(entry))
+            }
+        }
+    }
+
+    fn fs(&self) -> Arc<dyn FileSystem> {
+        self.fs.upgrade().unwrap()
+    }
+}
+
+#[derive(Debug)]
+enum FileKind {
+    Register,
+    Status,
+    Entry(Arc<BinfmtEntry>),
+}
+
+/// A file in the root directory.
+struct BinfmtMiscFile {
+    kind: FileKind,
+    metadata: RwLock<Metadata>,
+    fs: Weak<BinfmtMiscFs>,
+}
+
+impl BinfmtMiscFile {
+    fn new(kind: FileKind, fs: Weak<BinfmtMiscFs>) -> Arc<Self> {
+        let mode = if matches!(kind, FileKind::Register) {
+            0o200
+        } else {
+            0o644
+        };
+        Arc::new(Self {
+            kind,
+            metadata: RwLock::new(Metadata::new_file(
+                alloc_ino(),
+                InodeMode::from_bits_truncate(mode),
+                BLOCK_SIZE,
+            )),
+            fs,
+        })
+    }
+
+    fn is_of_entry(&self, entry: &Arc<BinfmtEntry>) -> bool {
+        matches!(&self.kind, FileKind::Entry(this_entry) if Arc::ptr_eq(this_entry, entry))
+    }
+
+    fn read(&self) -> Result<String> {
+        match &self.kind {
+            FileKind::Register => {
+                return_errno_with_message!(Errno::EINVAL, "the file is write-only")
+            }
+            FileKind::Status => Ok(String::from(if binfmt_misc::is_enabled() {
+                "enabled\n"
+            } else {
+                "disabled\n"
+            })),
+            FileKind::Entry(entry) => Ok(entry.status()),
+        }
+    }
+
+    fn write(&self, input: &str) -> Result<()> {
+        if let FileKind::Register = self.kind {
+            let current = Task::current().unwrap();
+            let fs_ref = current.as_thread_local().unwrap().borrow_fs();
+            let fs_resolver = fs_ref.resolver().read();
+            binfmt_misc::register(input, &fs_resolver)?;
+            return Ok(());
+        }
+
+        let command = match input.strip_suffix('\n').unwrap_or(input) {
+            "0" => Command::Disable,
+            "1" => Command::Enable,
+            "-1" => Command::Remove,
+            _ => return_errno_with_message!(Errno::EINVAL, "the command is invalid"),
+        };
+        match (&self.kind, command) {
+            (FileKind::Status, Command::Disable) => binfmt_misc::set_enabled(false),
+            (FileKind::Status, Command::Enable) => binfmt_misc::set_enabled(true),
+            (FileKind::Status, Command::Remove) => binfmt_misc::unregister_all(),
+            (FileKind::Entry(entry), Command::Disable) => entry.set_enabled(false),
+            (FileKind::Entry(entry), Command::Enable) => entry.set_enabled(true),
+            (FileKind::Entry(entry), Command::Remove) => binfmt_misc::unregister(entry.name())?,
+            (FileKind::Register, _) => unreachable!(),
+        }
+        Ok(())
+    }
+}
+
+/// A command written to `status` or the file of a format.
+enum Command {
+    Disable,
+    Enable,
+    Remove,
+}
+
+impl Inode for BinfmtMiscFile {
+    fn size(&self) -> usize {
+        0
+    }
+
+    fn resize(&self, _new_size: usize) -> Result<()> {
+        // Opening the files with `O_TRUNC` is allowed, but it has no effects.
+        Ok(())
+    }
+
+    fn metadata(&self) -> Metadata {
+        *self.metadata.read()
+    }
+
+    fn ino(&self) -> u64 {
+        self.metadata.read().ino
+    }
+
+    fn type_(&self) -> InodeType {
+        InodeType::File
+    }
+
+    fn mode(&self) -> Result<InodeMode> {
+        Ok(self.metadata.read().mode)
+    }
+
+    fn set_mode(&self, mode: InodeMode) -> Result<()> {
+        self.metadata.write().mode = mode;
+        Ok(())
+    }
+
+    fn owner(&self) -> Result<Uid> {
+        Ok(self.metadata.read().uid)
+    }
+
+    fn set_owner(&self, uid: Uid) -> Result<()> {
+        self.metadata.write().uid = uid;
+        Ok(())
+    }
+
+    fn group(&self) -> Result<Gid> {
+        Ok(self.metadata.read().gid)
+    }
+
+    fn set_group(&self, gid: Gid) -> Result<()> {
+        self.metadata.write().gid = gid;
+        Ok(())
+    }
+
+    fn atime(&self) -> Duration {
+        self.metadata.read().atime
+    }
+
+    fn set_atime(&self, time: Duration) {
+        self.metadata.write().atime = time;
+    }
+
+    fn mtime(&self) -> Duration {
+        self.metadata.read().mtime
+    }
+
+    fn set_mtime(&self, time: Duration) {
+        self.metadata.write().mtime = time;
+    }
+
+    fn ctime(&self) -> Duration {
+        self.metadata.read().ctime
+    }
+
+    fn set_ctime(&self, time: Duration) {
+        self.metadata.write().ctime = time;
+    }
+
+    fn read_at(&self, offset: usize, writer: &mut VmWriter) -> Result<usize> {
+        let data = self.read()?.into_bytes();
+        let start = data.len().min(offset);
+        let end = data.len().min(offset + writer.avail());
+        let len = end - start;
+        writer.write_fallible(&mut (&data[start..end]).into())?;
+        Ok(len)
+    }
+
+    fn write_at(&self, _offset: usize, reader: &mut VmReader) -> Result<usize> {
+        let len = reader.remain();
+        if len > PAGE_SIZE {
+            return_errno_with_message!(Errno::EINVAL, "the input is too long");
+        }
+
+        let mut buf = vec![0u8; len];
+        reader.read_fallible(&mut VmWriter::from(buf.as_mut_slice()))?;
+        let input = core::str::from_utf8(&buf)
+            .map_err(|_| Error::with_message(Errno::EINVAL, "the input is not valid UTF-8"))?;
+
+        self.write(input)?;
+        Ok(len)
+    }
+
+    fn is_dentry_cacheable(&self) -> bool {
+        // The files of the formats disappear when the formats are removed.
+        !matches!(self.kind, FileKind::Entry(_))
+    }
+
+    fn fs(&self) -> Arc<dyn FileSystem> {
+        self.fs.upgrade().unwrap()
+    }
+}
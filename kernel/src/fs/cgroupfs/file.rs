@@ -0,0 +1,411 @@
+// SPDX-License-Identifier: MPL-2.0
+
+use core::{fmt::Write, time::Duration};
+
+use align_ext::AlignExt;
+
+use super::{alloc_ino, CgroupFs, BLOCK_SIZE};
+use crate::{
+    fs::utils::{FileSystem, Inode, InodeMode, InodeType, Metadata},
+    prelude::*,
+    process::{
+        cgroup::{Cgroup, Controllers},
+        process_table, Gid, Pid, Uid,
+    },
+    sched::{
+        cpuset::{parse_cpu_list, CpuList},
+        SchedGroup,
+    },
+};
+
+/// The interface files in a cgroup directory.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(super) enum CgroupFileKind {
+    /// `cgroup.procs`: The PIDs of the processes in the cgroup. Writing a PID moves the process
+    /// into the cgroup.
+    Procs,
+    /// `cgroup.controllers`: The controllers that are enabled in the cgroup.
+    Controllers,
+    /// `cgroup.subtree_control`: The controllers that are enabled in the child cgroups, which can
+    /// be changed by writing `+<controller>` or `-<controller>`.
+    SubtreeControl,
+    /// `cpu.weight`: The weight of the cgroup, from 1 to 10000.
+    CpuWeight,
+    /// `cpu.max`: The bandwidth limit of the cgroup in the format of `$MAX $PERIOD`, where the
+    /// values are measured in microseconds and `$MAX` can be `max`.
+    CpuMax,
+    /// `cpu.stat`: The CPU usage statistics of the cgroup.
+    CpuStat,
+    /// `memory.current`: The memory usage of the cgroup in bytes.
+    MemoryCurrent,
+    /// `memory.max`: The memory usage limit of the cgroup in bytes, or `max`.
+    MemoryMax,
+    /// `cpuset.cpus`: The CPUs that the processes in the cgroup can run on.
+    CpusetCpus,
+    /// `cpuset.cpus.effective`: The CPUs that the processes in the cgroup actually run on.
+    CpusetCpusEffective,
+}
+
+const MIN_CPU_PERIOD_US: u64 = 1_000;
+const MAX_CPU_PERIOD_US: u64 = 1_000_000;
+const MIN_CPU_QUOTA_US: u64 = 1_000;
+
+impl CgroupFileKind {
+    pub(super) const ALL: [CgroupFileKind; 10] = [
+        Self::Procs,
+        Self::Controllers,
+        Self::SubtreeControl,
+        Self::CpuWeight,
+        Self::CpuMax,
+        Self::CpuStat,
+        Self::MemoryCurrent,
+        Self::MemoryMax,
+        Self::CpusetCpus,
+        Self::CpusetCpusEffective,
+    ];
+
+    pub(super) fn name(self) -> &'static str {
+        match self {
+            Self::Procs => "cgroup.procs",
+            Self::Controllers => "cgroup.controllers",
+            Self::SubtreeControl => "cgroup.subtree_control",
+            Self::CpuWeight => "cpu.weight",
+            Self::CpuMax => "cpu.max",
+            Self::CpuStat => "cpu.stat",
+            Self::MemoryCurrent => "memory.current",
+            Self::MemoryMax => "memory.max",
+            Self::CpusetCpus => "cpuset.cpus",
+            Self::CpusetCpusEffective => "cpuset.cpus.effective",
+        }
+    }
+
+    pub(super) fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|kind| kind.name() == name)
+    }
+
+    fn is_writable(self) -> bool {
+        matches!(
+            self,
+            Self::Procs
+                | Self::SubtreeControl
+                | Self::CpuWeight
+                | Self::CpuMax
+                | Self::MemoryMax
+                | Self::CpusetCpus
+        )
+    }
+
+    /// Returns whether the file exists in the cgroup.
+    ///
+    /// The files of a controller only exist if the controller is enabled in the cgroup. Most of
+    /// them do not exist in the root cgroup, since the root cgroup cannot be limited.
+    pub(super) fn is_available(self, cgroup: &Cgroup) -> bool {
+        let is_root = cgroup.parent().is_none();
+        let controllers = cgroup.controllers();
+
+        match self {
+            Self::Procs | Self::Controllers | Self::SubtreeControl | Self::CpuStat => true,
+            Self::CpuWeight | Self::CpuMax => !is_root && controllers.contains(Controllers::CPU),
+            Self::MemoryCurrent | Self::MemoryMax => {
+                !is_root && controllers.contains(Controllers::MEMORY)
+            }
+            Self::CpusetCpus => !is_root && controllers.contains(Controllers::CPUSET),
+            Self::CpusetCpusEffective => controllers.contains(Controllers::CPUSET),
+        }
+    }
+
+    fn read(self, cgroup: &Cgroup) -> String {
+        let mut output = String::new();
+
+        match self {
+            Self::Procs => {
+                // Only the processes that are visible to the reader are shown.
+                let pid_ns = current!().pid_ns().clone();
+                let mut pids: Vec<Pid> = cgroup
+                    .processes()
+                    .iter()
+                    .filter_map(|process| pid_ns.local_id(process.pid()))
+                    .collect();
+                pids.sort_unstable();
+                for pid in pids {
+                    writeln!(output, "{}", pid).unwrap();
+                }
+            }
+            Self::Controllers => writeln!(output, "{}", cgroup.controllers().to_names()).unwrap(),
+            Self::SubtreeControl => {
+                writeln!(output, "{}", cgroup.subtree_control().to_names()).unwrap()
+            }
+            Self::CpuWeight => writeln!(output, "{}", cgroup.sched_group().weight()).unwrap(),
+            Self::CpuMax => {
+                let (quota, period) = cgroup.sched_group().max();
+                match quota {
+                    Some(quota) => write!(output, "{}", quota.as_micros()).unwrap(),
+                    None => output.push_str("max"),
+                }
+                writeln!(output, " {}", period.as_micros()).unwrap();
+            }
+            Self::CpuStat => {
+                let stat = cgroup.sched_group().stat();
+                writeln!(output, "usage_usec {}", stat.usage.as_micros()).unwrap();
+                writeln!(output, "nr_periods {}", stat.nr_periods).unwrap();
+                writeln!(output, "nr_throttled {}", stat.nr_throttled).unwrap();
+            }
+            Self::MemoryCurrent => writeln!(output, "{}", cgroup.memory_group().current()).unwrap(),
+            Self::MemoryMax => match cgroup.memory_group().max() {
+                Some(max) => writeln!(output, "{}", max).unwrap(),
+                None => output.push_str("max\n"),
+            },
+            Self::CpusetCpus => writeln!(output, "{}", CpuList(&cgroup.cpuset().cpus())).unwrap(),
+            Self::CpusetCpusEffective => {
+                writeln!(output, "{}", CpuList(&cgroup.effective_cpuset().cpus())).unwrap()
+            }
+        }
+
+        output
+    }
+
+    fn write(self, cgroup: &Arc<Cgroup>, input: &str) -> Result<()> {
+        let input = input.trim();
+
+        match self {
+            Self::Procs => {
+                let pid = parse_int::<Pid>(input)?;
+                let process = if pid == 0 {
+                    current!()
+                } else {
+                    current!()
+                        .pid_ns()
+                        .global_id(pid)
+                        .and_then(process_table::get_process)
+                        .ok_or_else(|| {
+                            Error::with_message(Errno::ESRCH, "the process does not exist")
+                        })?
+                };
+                cgroup.attach(&process)?;
+            }
+            Self::SubtreeControl => {
+                let mut enable = Controllers::empty();
+                let mut disable = Controllers::empty();
+                for token in input.split_whitespace() {
+                    let (controllers, name) = if let Some(name) = token.strip_prefix('+') {
+                        (&mut enable, name)
+                    } else if let Some(name) = token.strip_prefix('-') {
+                        (&mut disable, name)
+                    } else {
+                        return_errno_with_message!(Errno::EINVAL, "the prefix must be `+` or `-`");
+                    };
+                    let Some(controller) = Controllers::from_name(name) else {
+                        return_errno_with_message!(Errno::EINVAL, "the controller is unknown");
+                    };
+                    *controllers |= controller;
+                }
+                cgroup.update_subtree_control(enable - disable, disable)?;
+            }
+            Self::CpuWeight => {
+                let weight = parse_int::<u32>(input)?;
+                if !(SchedGroup::MIN_WEIGHT..=SchedGroup::MAX_WEIGHT).contains(&weight) {
+                    return_errno_with_message!(Errno::ERANGE, "the weight is out of range");
+                }
+                cgroup.sched_group().set_weight(weight);
+            }
+            Self::CpuMax => {
+                let mut tokens = input.split_whitespace();
+                let quota = match tokens.next() {
+                    Some("max") => None,
+                    Some(quota) => Some(parse_int::<u64>(quota)?),
+                    None => return_errno_with_message!(Errno::EINVAL, "the quota is missing"),
+                };
+                let period = match tokens.next() {
+                    Some(period) => parse_int::<u64>(period)?,
+                    None => cgroup.sched_group().max().1.as_micros() as u64,
+                };
+                if tokens.next().is_some() {
+                    return_errno_with_message!(Errno::EINVAL, "too many values");
+                }
+
+                if !(MIN_CPU_PERIOD_US..=MAX_CPU_PERIOD_US).contains(&period)
+                    || quota.is_some_and(|quota| quota < MIN_CPU_QUOTA_US)
+                {
+                    return_errno_with_message!(Errno::EINVAL, "the bandwidth is out of range");
+                }
+                cgroup.sched_group().set_max(
+                    quota.map(Duration::from_micros),
+                    Duration::from_micros(period),
+                );
+            }
+            Self::MemoryMax => {
+                let max = if input == "max" {
+                    None
+                } else {
+                    Some(parse_size(input)?.align_down(PAGE_SIZE))
+                };
+                cgroup.memory_group().set_max(max);
+            }
+            Self::CpusetCpus => cgroup.set_cpus(parse_cpu_list(input)?)?,
+            Self::Controllers | Self::CpuStat | Self::MemoryCurrent | Self::CpusetCpusEffective => {
+                return_errno_with_message!(Errno::EPERM, "the file is read-only")
+            }
+        }
+
+        Ok(())
+    }
+}
+
+fn parse_int<T: core::str::FromStr>(input: &str) -> Result<T> {
+    input
+        .parse()
+        .map_err(|_| Error::with_message(Errno::EINVAL, "the value is not a valid integer"))
+}
+
+/// Parses a size in bytes with an optional suffix of `K`, `M`, `G`, or `T`.
+fn parse_size(input: &str) -> Result<usize> {
+    let (number, shift) = match input.as_bytes().last() {
+        Some(b'k' | b'K') => (&input[..input.len() - 1], 10),
+        Some(b'm' | b'M') => (&input[..input.len() - 1], 20),
+        Some(b'g' | b'G') => (&input[..input.len() - 1], 30),
+        Some(b't' | b'T') => (&input[..input.len() - 1], 40),
+        _ => (input, 0),
+    };
+
+    parse_int::<usize>(number)?
+        .checked_mul(1 << shift)
+        .ok_or_else(|| Error::with_message(Errno::EINVAL, "the size is too large"))
+}
+
+/// An interface file in a cgroup directory.
+pub(super) struct CgroupFile {
+    cgroup: Arc<Cgroup>,
+    kind: CgroupFileKind,
+    metadata: RwLock<Metadata>,
+    fs: Weak<CgroupFs>,
+}
+
+impl CgroupFile {
+    pub(super) fn new(cgroup: Arc<Cgroup>, kind: CgroupFileKind, fs: Weak<CgroupFs>) -> Arc<Self> {
+        let mode = if kind.is_writable() { 0o644 } else { 0o444 };
+        Arc::new(Self {
+            cgroup,
+            kind,
+            metadata: RwLock::new(Metadata::new_file(
+                alloc_ino(),
+                InodeMode::from_bits_truncate(mode),
+                BLOCK_SIZE,
+            )),
+            fs,
+        })
+    }
+
+    pub(super) fn kind(&self) -> CgroupFileKind {
+        self.kind
+    }
+}
+
+impl Inode for CgroupFile {
+    fn size(&self) -> usize {
+        0
+    }
+
+    fn resize(&self, _new_size: usize) -> Result<()> {
+        // Opening the files with `O_TRUNC` is allowed, but it has no effects.
+        if !self.kind.is_writable() {
+            return_errno_with_message!(Errno::EPERM, "the file is read-only");
+        }
+        Ok(())
+    }
+
+    fn metadata(&self) -> Metadata {
+        *self.metadata.read()
+    }
+
+    fn ino(&self) -> u64 {
+        self.metadata.read().ino
+    }
+
+    fn type_(&self) -> InodeType {
+        InodeType::File
+    }
+
+    fn mode(&self) -> Result<InodeMode> {
+        Ok(self.metadata.read().mode)
+    }
+
+    fn set_mode(&self, mode: InodeMode) -> Result<()> {
+        self.metadata.write().mode = mode;
+        Ok(())
+    }
+
+    fn owner(&self) -> Result<Uid> {
+        Ok(self.metadata.read().uid)
+    }
+
+    fn set_owner(&self, uid: Uid) -> Result<()> {
+        self.metadata.write().uid = uid;
+        Ok(())
+    }
+
+    fn group(&self) -> Result<Gid> {
+        Ok(self.metadata.read().gid)
+    }
+
+    fn set_group(&self, gid: Gid) -> Result<()> {
+        self.metadata.write().gid = gid;
+        Ok(())
+    }
+
+    fn atime(&self) -> Duration {
+        self.metadata.read().atime
+    }
+
+    fn set_atime(&self, time: Duration) {
+        self.metadata.write().atime = time;
+    }
+
+    fn mtime(&self) -> Duration {
+        self.metadata.read().mtime
+    }
+
+    fn set_mtime(&self, time: Duration) {
+        self.metadata.write().mtime = time;
+    }
+
+    fn ctime(&self) -> Duration {
+        self.metadata.read().ctime
+    }
+
+    fn set_ctime(&self, time: Duration) {
+        self.metadata.write().ctime = time;
+    }
+
+    fn read_at(&self, offset: usize, writer: &mut VmWriter) -> Result<usize> {
+        let data = self.kind.read(&self.cgroup).into_bytes();
+        let start = data.len().min(offset);
+        let end = data.len().min(offset + writer.avail());
+        let len = end - start;
+        writer.write_fallible(&mut (&data[start..end]).into())?;
+        Ok(len)
+    }
+
+    fn write_at(&self, _offset: usize, reader: &mut VmReader) -> Result<usize> {
+        let len = reader.remain();
+        if len > PAGE_SIZE {
+            return_errno_with_message!(Errno::EINVAL, "the input is too long");
+        }
+
+        let mut buf = vec![0u8; len];
+        reader.read_fallible(&mut VmWriter::from(buf.as_mut_slice()))?;
+        let input = core::str::from_utf8(&buf)
+            .map_err(|_| Error::with_message(Errno::EINVAL, "the input is not valid UTF-8"))?;
+
+        self.kind.write(&self.cgroup, input)?;
+        Ok(len)
+    }
+
+    fn is_dentry_cacheable(&self) -> bool {
+        // The file may disappear when the controller is disabled.
+        false
+    }
+
+    fn fs(&self) -> Arc<dyn FileSystem> {
+        self.fs.upgrade().unwrap()
+    }
+}
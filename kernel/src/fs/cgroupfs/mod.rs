@@ -0,0 +1,302 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! The cgroup filesystem (cgroupfs) of the unified hierarchy (cgroup v2).
+//!
+//! Every directory represents a [`Cgroup`], where creating or removing a directory creates or
+//! removes a child cgroup. The interface files in a directory are used to move processes between
+//! cgroups and to configure the controllers. See [`file::CgroupFileKind`] for the available files.
+
+use core::{
+    sync::atomic::{AtomicU64, Ordering},
+    time::Duration,
+};
+
+use spin::Once;
+
+use self::file::{CgroupFile, CgroupFileKind};
+use crate::{
+    fs::utils::{
+        DirentVisitor, FileSystem, FsFlags, Inode, InodeMode, InodeType, Metadata, MknodType,
+        SuperBlock, NAME_MAX,
+    },
+    prelude::*,
+    process::{cgroup::Cgroup, Gid, Uid},
+};
+
+mod file;
+
+const CGROUP2_SUPER_MAGIC: u64 = 0x6367_7270;
+const BLOCK_SIZE: usize = 4096;
+const ROOT_INO: u64 = 1;
+
+/// The cgroup filesystem.
+///
+/// All the mounted cgroupfs instances share the same hierarchy, just like Linux.
+pub struct CgroupFs {
+    sb: SuperBlock,
+    root: Arc<CgroupDir>,
+}
+
+impl CgroupFs {
+    /// Returns the cgroup filesystem.
+    pub fn singleton() -> &'static Arc<CgroupFs> {
+        static SINGLETON: Once<Arc<CgroupFs>> = Once::new();
+
+        SINGLETON.call_once(|| {
+            Arc::new_cyclic(|weak_fs| Self {
+                sb: SuperBlock::new(CGROUP2_SUPER_MAGIC, BLOCK_SIZE, NAME_MAX),
+                root: CgroupDir::new(Cgroup::root().clone(), Weak::new(), weak_fs.clone()),
+            })
+        })
+    }
+}
+
+fn alloc_ino() -> u64 {
+    static NEXT_INO: AtomicU64 = AtomicU64::new(ROOT_INO);
+
+    NEXT_INO.fetch_add(1, Ordering::Relaxed)
+}
+
+impl FileSystem for CgroupFs {
+    fn sync(&self) -> Result<()> {
+        Ok(())
+    }
+
+    fn root_inode(&self) -> Arc<dyn Inode> {
+        self.root.clone()
+    }
+
+    fn sb(&self) -> SuperBlock {
+        self.sb.clone()
+    }
+
+    fn flags(&self) -> FsFlags {
+        FsFlags::empty()
+    }
+
+    fn name(&self) -> &'static str {
+        "cgroup2"
+    }
+}
+
+/// A directory that represents a cgroup.
+struct CgroupDir {
+    cgroup: Arc<Cgroup>,
+    files: Vec<Arc<CgroupFile>>,
+    children: Mutex<BTreeMap<String, Arc<CgroupDir>>>,
+    metadata: RwLock<Metadata>,
+    parent: Weak<CgroupDir>,
+    this: Weak<CgroupDir>,
+    fs: Weak<CgroupFs>,
+}
+
+impl CgroupDir {
+    fn new(cgroup: Arc<Cgroup>, parent: Weak<CgroupDir>, fs: Weak<CgroupFs>) -> Arc<Self> {
+        let ino = alloc_ino();
+        let files = CgroupFileKind::ALL
+            .iter()
+            .map(|kind| CgroupFile::new(cgroup.clone(), *kind, fs.clone()))
+            .collect();
+
+        Arc::new_cyclic(|this| Self {
+            cgroup,
+            files,
+            children: Mutex::new(BTreeMap::new()),
+            metadata: RwLock::new(Metadata::new_dir(
+                ino,
+                InodeMode::from_bits_truncate(0o755),
+                BLOCK_SIZE,
+            )),
+            parent,
+            this: this.clone(),
+            fs,
+        })
+    }
+
+    /// Returns the interface files that are available in the cgroup.
+    fn visible_files(&self) -> impl Iterator<Item = &Arc<CgroupFile>> {
+        self.files
+            .iter()
+            .filter(|file| file.kind().is_available(&self.cgroup))
+    }
+
+    fn this(&self) -> Arc<CgroupDir> {
+        self.this.upgrade().unwrap()
+    }
+}
+
+impl Inode for CgroupDir {
+    fn size(&self) -> usize {
+        0
+    }
+
+    fn resize(&self, _new_size: usize) -> Result<()> {
+        Err(Error::new(Errno::EISDIR))
+    }
+
+    fn metadata(&self) -> Metadata {
+        *self.metadata.read()
+    }
+
+    fn ino(&self) -> u64 {
+        self.metadata.read().ino
+    }
+
+    fn type_(&self) -> InodeType {
+        InodeType::Dir
+    }
+
+    fn mode(&self) -> Result<InodeMode> {
+        Ok(self.metadata.read().mode)
+    }
+
+    fn set_mode(&self, mode: InodeMode) -> Result<()> {
+        self.metadata.write().mode = mode;
+        Ok(())
+    }
+
+    fn owner(&self) -> Result<Uid> {
+        Ok(self.metadata.read().uid)
+    }
+
+    fn set_owner(&self, uid: Uid) -> Result<()> {
+        self.metadata.write().uid = uid;
+        Ok(())
+    }
+
+    fn group(&self) -> Result<Gid> {
+        Ok(self.metadata.read().gid)
+    }
+
+    fn set_group(&self, gid: Gid) -> Result<()> {
+        self.metadata.write().gid = gid;
+        Ok(())
+    }
+
+    fn atime(&self) -> Duration {
+        self.metadata.read().atime
+    }
+
+    fn set_atime(&self, time: Duration) {
+        self.metadata.write().atime = time;
+    }
+
+    fn mtime(&self) -> Duration {
+        self.metadata.read().mtime
+    }
+
+    fn set_mtime(&self, time: Duration) {
+        self.metadata.write().mtime = time;
+    }
+
+    fn ctime(&self) -> Duration {
+        self.metadata.read().ctime
+    }
+
+    fn set_ctime(&self, time: Duration) {
+        self.metadata.write().ctime = time;
+    }
+
+    fn create(&self, name: &str, type_: InodeType, mode: InodeMode) -> Result<Arc<dyn Inode>> {
+        if type_ != InodeType::Dir {
+            return_errno_with_message!(Errno::EPERM, "only directories can be created");
+        }
+        if CgroupFileKind::from_name(name).is_some() {
+            return_errno_with_message!(Errno::EEXIST, "the name is used by an interface file");
+        }
+
+        let mut children = self.children.lock();
+        let cgroup = self.cgroup.new_child(name)?;
+
+        let child = CgroupDir::new(cgroup, self.this.clone(), self.fs.clone());
+        child.set_mode(mode)?;
+        children.insert(name.to_string(), child.clone());
+
+        Ok(child)
+    }
+
+    fn mknod(&self, _name: &str, _mode: InodeMode, _type_: MknodType) -> Result<Arc<dyn Inode>> {
+        Err(Error::new(Errno::EPERM))
+    }
+
+    fn readdir_at(&self, offset: usize, visitor: &mut dyn DirentVisitor) -> Result<usize> {
+        let mut entries: Vec<(String, u64, InodeType)> = vec![
+            (String::from("."), self.ino(), InodeType::Dir),
+            (
+                String::from(".."),
+                self.parent.upgrade().unwrap_or_else(|| self.this()).ino(),
+                InodeType::Dir,
+            ),
+        ];
+        entries.extend(
+            self.visible_files()
+                .map(|file| (file.kind().name().to_string(), file.ino(), InodeType::File)),
+        );
+        entries.extend(
+            self.children
+                .lock()
+                .iter()
+                .map(|(name, child)| (name.clone(), child.ino(), InodeType::Dir)),
+        );
+
+        let mut iterate_offset = offset;
+        for (name, ino, type_) in entries.iter().skip(offset) {
+            if let Err(err) = visitor.visit(name, *ino, *type_, iterate_offset) {
+                if iterate_offset == offset {
+                    return Err(err);
+                }
+                break;
+            }
+            iterate_offset += 1;
+        }
+
+        Ok(iterate_offset - offset)
+    }
+
+    fn link(&self, _old: &Arc<dyn Inode>, _name: &str) -> Result<()> {
+        Err(Error::new(Errno::EPERM))
+    }
+
+    fn unlink(&self, _name: &str) -> Result<()> {
+        Err(Error::new(Errno::EPERM))
+    }
+
+    fn rmdir(&self, name: &str) -> Result<()> {
+        let mut children = self.children.lock();
+        if !children.contains_key(name) {
+            return_errno_with_message!(Errno::ENOENT, "the cgroup does not exist");
+        }
+
+        self.cgroup.remove_child(name)?;
+        children.remove(name);
+
+        Ok(())
+    }
+
+    fn lookup(&self, name: &str) -> Result<Arc<dyn Inode>> {
+        if name == "." {
+            return Ok(self.this());
+        }
+        if name == ".." {
+            return Ok(self.parent.upgrade().unwrap_or_else(|| self.this()));
+        }
+
+        if let Some(file) = self.visible_files().find(|file| file.kind().name() == name) {
+            return Ok(file.clone());
+        }
+
+        self.children
+            .lock()
+            .get(name)
+            .map(|child| child.clone() as Arc<dyn Inode>)
+            .ok_or_else(|| Error::with_message(Errno::ENOENT, "the file does not exist"))
+    }
+
+    fn rename(&self, _old_name: &str, _target: &Arc<dyn Inode>, _new_name: &str) -> Result<()> {
+        Err(Error::new(Errno::EPERM))
+    }
+
+    fn fs(&self) -> Arc<dyn FileSystem> {
+        self.fs.upgrade().unwrap()
+    }
+}
@@ -102,6 +102,10 @@ impl FileSystem for DevPts {
     fn flags(&self) -> FsFlags {
         FsFlags::empty()
     }
+
+    fn name(&self) -> &'static str {
+        "devpts"
+    }
 }
 
 struct RootInode {
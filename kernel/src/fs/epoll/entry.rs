@@ -147,12 +147,20 @@ impl Entry {
 
         inner.event = event;
         inner.flags = flags;
+        inner
+            .poller
+            .set_exclusive(flags.contains(EpollFlags::EXCLUSIVE));
 
         self.observer.set_enabled(&inner);
 
         file.poll(event.events, Some(&mut inner.poller))
     }
 
+    /// Returns whether the epoll entry is exclusive (i.e., added with `EPOLLEXCLUSIVE`).
+    pub(super) fn is_exclusive(&self) -> bool {
+        self.inner.lock().flags.contains(EpollFlags::EXCLUSIVE)
+    }
+
     /// Shuts down the epoll entry.
     ///
     /// This method needs to be called in response to `EpollCtl::Del`.
@@ -269,6 +277,14 @@ impl events::Observer<IoEvents> for Observer {
     fn on_events(&self, _events: &IoEvents) {
         self.ready_set.push(self);
     }
+
+    fn on_exclusive_events(&self, events: &IoEvents) -> bool {
+        self.on_events(events);
+
+        // The events are taken care of only if someone is waiting on the epoll file.
+        // Otherwise, other epoll files should also be notified.
+        self.is_enabled() && self.ready_set.has_waiters()
+    }
 }
 
 /// A set of ready epoll entries.
@@ -318,6 +334,11 @@ impl ReadySet {
         self.pollee.notify(IoEvents::IN);
     }
 
+    /// Returns whether someone is waiting for the ready set (i.e., polling the epoll file).
+    fn has_waiters(&self) -> bool {
+        self.pollee.has_pollers()
+    }
+
     pub(super) fn lock_pop(&self) -> ReadySetPopIter {
         ReadySetPopIter {
             ready_set: self,
@@ -87,6 +87,10 @@ impl EpollFile {
     ) -> Result<()> {
         self.warn_unsupported_flags(&ep_flags);
 
+        if ep_flags.contains(EpollFlags::EXCLUSIVE) {
+            check_exclusive(&file, &ep_event, &ep_flags)?;
+        }
+
         // Add the new entry to the interest list and start monitoring its events
         let ready_entry = {
             let mut interest = self.interest.lock();
@@ -147,6 +151,13 @@ impl EpollFile {
     ) -> Result<()> {
         self.warn_unsupported_flags(&new_ep_flags);
 
+        if new_ep_flags.contains(EpollFlags::EXCLUSIVE) {
+            return_errno_with_message!(
+                Errno::EINVAL,
+                "EPOLLEXCLUSIVE cannot be specified when modifying an entry"
+            );
+        }
+
         // Update the epoll entry
         let ready_entry = {
             let interest = self.interest.lock();
@@ -155,6 +166,9 @@ impl EpollFile {
                 interest.get(&EntryKey::from((fd, &file))).ok_or_else(|| {
                     Error::with_message(Errno::ENOENT, "the file is not in the interest list")
                 })?;
+            if entry.is_exclusive() {
+                return_errno_with_message!(Errno::EINVAL, "an exclusive entry cannot be modified");
+            }
             let events = entry.update(new_ep_event, new_ep_flags);
 
             if !events.is_empty() {
@@ -240,7 +254,7 @@ impl EpollFile {
     }
 
     fn warn_unsupported_flags(&self, flags: &EpollFlags) {
-        if flags.intersects(EpollFlags::EXCLUSIVE | EpollFlags::WAKE_UP) {
+        if flags.intersects(EpollFlags::WAKE_UP) {
             warn!("{:?} contains unsupported flags", flags);
         }
     }
@@ -277,6 +291,39 @@ impl FileLike for EpollFile {
     }
 }
 
+/// Checks whether the file can be added to the interest list with `EPOLLEXCLUSIVE`.
+///
+/// See the `EINVAL` section in <https://man7.org/linux/man-pages/man2/epoll_ctl.2.html>.
+fn check_exclusive(
+    file: &Arc<dyn FileLike>,
+    ep_event: &EpollEvent,
+    ep_flags: &EpollFlags,
+) -> Result<()> {
+    if ep_flags.contains(EpollFlags::ONE_SHOT) {
+        return_errno_with_message!(
+            Errno::EINVAL,
+            "EPOLLEXCLUSIVE cannot be specified with EPOLLONESHOT"
+        );
+    }
+
+    let allowed_events = IoEvents::IN | IoEvents::OUT | IoEvents::ERR | IoEvents::HUP;
+    if !allowed_events.contains(ep_event.events) {
+        return_errno_with_message!(
+            Errno::EINVAL,
+            "invalid events are specified with EPOLLEXCLUSIVE"
+        );
+    }
+
+    if file.downcast_ref::<EpollFile>().is_some() {
+        return_errno_with_message!(
+            Errno::EINVAL,
+            "EPOLLEXCLUSIVE cannot be specified for epoll files"
+        );
+    }
+
+    Ok(())
+}
+
 struct EntryHolder(Arc<Entry>);
 
 impl PartialOrd for EntryHolder {
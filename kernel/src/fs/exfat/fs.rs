@@ -421,6 +421,10 @@ impl FileSystem for ExfatFS {
     fn flags(&self) -> FsFlags {
         FsFlags::DENTRY_UNEVICTABLE
     }
+
+    fn name(&self) -> &'static str {
+        "exfat"
+    }
 }
 
 #[derive(Clone, Debug, Default)]
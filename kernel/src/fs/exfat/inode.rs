@@ -1265,10 +1265,14 @@ impl Inode for ExfatInode {
                 .align_down(sector_size);
             (start, end - start)
         };
+        if read_len == 0 {
+            return Ok(0);
+        }
 
+        // Write back the dirty pages first, so that the device holds the latest data.
         inner
             .page_cache
-            .discard_range(read_off..read_off + read_len);
+            .evict_range(read_off..read_off + read_len)?;
 
         let mut buf_offset = 0;
         let bio_segment = BioSegment::alloc(1, BioDirection::FromDevice);
@@ -12,36 +12,48 @@ use super::{inode::MAX_FNAME_LEN, prelude::*};
 pub struct DirEntry {
     /// The header part.
     header: DirEntryHeader,
-    /// Name of the entry, up to 255 bytes (excluding the null terminator).
-    name: CStr256,
+    /// Name of the entry, up to 255 bytes.
+    ///
+    /// The name is not necessarily a valid UTF-8 string, e.g., if it is encrypted.
+    name: [u8; MAX_FNAME_LEN],
 }
 
 impl DirEntry {
     /// Constructs a new `DirEntry` object with the specified inode (`ino`),
     /// name (`name`), and file type (`inode_type`).
-    pub(super) fn new(ino: u32, name: &str, inode_type: InodeType) -> Self {
+    pub(super) fn new(ino: u32, name: &[u8], inode_type: InodeType) -> Self {
         debug_assert!(name.len() <= MAX_FNAME_LEN);
 
         let record_len = (Self::header_len() + name.len()).align_up(4) as u16;
+        let header = DirEntryHeader {
+            ino,
+            record_len,
+            name_len: name.len() as u8,
+            inode_type: DirEntryFileType::from(inode_type) as _,
+        };
+        Self::with_header(header, name)
+    }
+
+    /// Constructs a `DirEntry` with the header and the name.
+    fn with_header(header: DirEntryHeader, name: &[u8]) -> Self {
+        debug_assert_eq!(header.name_len as usize, name.len());
+
+        let mut name_buf = [0; MAX_FNAME_LEN];
+        name_buf[..name.len()].copy_from_slice(name);
         Self {
-            header: DirEntryHeader {
-                ino,
-                record_len,
-                name_len: name.len() as u8,
-                inode_type: DirEntryFileType::from(inode_type) as _,
-            },
-            name: CStr256::from(name),
+            header,
+            name: name_buf,
         }
     }
 
     /// Constructs a `DirEntry` with the name "." and `self_ino` as its inode.
     pub(super) fn self_entry(self_ino: u32) -> Self {
-        Self::new(self_ino, ".", InodeType::Dir)
+        Self::new(self_ino, b".", InodeType::Dir)
     }
 
     /// Constructs a `DirEntry` with the name ".." and `parent_ino` as its inode.
     pub(super) fn parent_entry(parent_ino: u32) -> Self {
-        Self::new(parent_ino, "..", InodeType::Dir)
+        Self::new(parent_ino, b"..", InodeType::Dir)
     }
 
     /// Returns a reference to the header.
@@ -60,8 +72,8 @@ impl DirEntry {
     }
 
     /// Returns the name.
-    pub fn name(&self) -> &str {
-        self.name.as_str().unwrap()
+    pub fn name(&self) -> &[u8] {
+        &self.name[..self.header.name_len as usize]
     }
 
     /// Returns the inode type of the entry.
@@ -179,37 +191,34 @@ impl<'a> DirEntryReader<'a> {
     pub fn iter_entries(&'a mut self) -> impl Iterator<Item = DirEntry> + 'a {
         let iter = self.iter();
         iter.filter_map(|entry_item| match self.read_name(&entry_item) {
-            Ok(name_buf) => Some(DirEntry {
-                header: entry_item.header,
-                name: CStr256::from(name_buf),
-            }),
+            Ok(name_buf) => Some(DirEntry::with_header(entry_item.header, name_buf)),
             Err(_) => None,
         })
     }
 
     /// Whether the directory contains an entry with the given name.
-    pub fn contains_entry(&mut self, name: &str) -> bool {
+    pub fn contains_entry(&mut self, name: &[u8]) -> bool {
         let mut iter = self.iter();
         iter.any(|entry_item| {
             if entry_item.name_len() != name.len() {
                 return false;
             }
             match self.read_name(&entry_item) {
-                Ok(name_buf) => name_buf == name.as_bytes(),
+                Ok(name_buf) => name_buf == name,
                 Err(_) => false,
             }
         })
     }
 
     /// Returns the target entry with the given name.
-    pub fn find_entry_item(&mut self, name: &str) -> Option<DirEntryItem> {
+    pub fn find_entry_item(&mut self, name: &[u8]) -> Option<DirEntryItem> {
         let mut iter = self.iter();
         iter.find(|entry_item| {
             if entry_item.name_len() != name.len() {
                 return false;
             }
             match self.read_name(entry_item) {
-                Ok(name_buf) => name_buf == name.as_bytes(),
+                Ok(name_buf) => name_buf == name,
                 Err(_) => false,
             }
         })
@@ -336,11 +345,8 @@ impl DirEntryItem {
     }
 
     /// Converts to a `DirEntry` given the name.
-    pub fn to_entry_with_name(&self, name: &str) -> DirEntry {
-        DirEntry {
-            header: self.header,
-            name: CStr256::from(name),
-        }
+    pub fn to_entry_with_name(&self, name: &[u8]) -> DirEntry {
+        DirEntry::with_header(self.header, name)
     }
 }
 
@@ -366,10 +372,9 @@ impl<'a> DirEntryWriter<'a> {
         self.page_cache
             .pages()
             .write_val(self.offset, entry.header())?;
-        self.page_cache.pages().write_bytes(
-            self.offset + DirEntry::header_len(),
-            entry.name().as_bytes(),
-        )?;
+        self.page_cache
+            .pages()
+            .write_bytes(self.offset + DirEntry::header_len(), entry.name())?;
 
         self.offset += entry.record_len();
         Ok(())
@@ -411,14 +416,13 @@ impl<'a> DirEntryWriter<'a> {
     }
 
     /// Removes and returns an existing `DirEntry` indicated by `name`.
-    pub fn remove_entry(&mut self, name: &str) -> Result<DirEntry> {
+    pub fn remove_entry(&mut self, name: &[u8]) -> Result<DirEntry> {
         let self_entry_record_len = DirEntry::self_entry(0).record_len();
         let reader = DirEntryReader::new(self.page_cache, 0).iter();
         let next_reader = DirEntryReader::new(self.page_cache, self_entry_record_len).iter();
         let Some((mut pre_entry_item, entry_item)) =
             reader.zip(next_reader).find(|(_, entry_item)| {
-                entry_item.name_len() == name.len()
-                    && self.read_name(entry_item).unwrap() == name.as_bytes()
+                entry_item.name_len() == name.len() && self.read_name(entry_item).unwrap() == name
             })
         else {
             return_errno!(Errno::ENOENT);
@@ -452,7 +456,7 @@ impl<'a> DirEntryWriter<'a> {
     ///
     /// It will moves the `DirEntry` to another position,
     /// if the record length is not big enough.
-    pub fn rename_entry(&mut self, old_name: &str, new_name: &str) -> Result<()> {
+    pub fn rename_entry(&mut self, old_name: &[u8], new_name: &[u8]) -> Result<()> {
         let entry_item = DirEntryReader::new(self.page_cache, self.offset)
             .find_entry_item(old_name)
             .ok_or(Error::new(Errno::ENOENT))?;
@@ -30,6 +30,10 @@ impl FileSystem for Ext2 {
     fn flags(&self) -> FsFlags {
         FsFlags::empty()
     }
+
+    fn name(&self) -> &'static str {
+        "ext2"
+    }
 }
 
 impl From<RwMutexReadGuard<'_, Dirty<Ext2SuperBlock>>> for SuperBlock {
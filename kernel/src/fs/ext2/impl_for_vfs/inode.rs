@@ -9,13 +9,14 @@ use aster_rights::Full;
 use crate::{
     fs::{
         ext2::{FilePerm, Inode as Ext2Inode},
+        fscrypt::EncryptionPolicy,
         utils::{
             DirentVisitor, Extension, FallocMode, FileSystem, Inode, InodeMode, InodeType,
             IoctlCmd, Metadata, MknodType,
         },
     },
     prelude::*,
-    process::{Gid, Uid},
+    process::{credentials::capabilities::CapSet, posix_thread::AsPosixThread, Gid, Uid},
     vm::vmo::Vmo,
 };
 
@@ -171,8 +172,35 @@ impl Inode for Ext2Inode {
         self.fallocate(mode, offset, len)
     }
 
+    fn prepare_open(&self) -> Result<()> {
+        self.prepare_open()
+    }
+
     fn ioctl(&self, cmd: IoctlCmd, arg: usize) -> Result<i32> {
-        Err(Error::new(Errno::EINVAL))
+        match cmd {
+            IoctlCmd::FS_IOC_SET_ENCRYPTION_POLICY => {
+                let policy = EncryptionPolicy::read_from_user(arg)?;
+
+                let credentials = current_thread!().as_posix_thread().unwrap().credentials();
+                if credentials.fsuid() != Uid::new(self.uid())
+                    && !credentials.effective_capset().contains(CapSet::FOWNER)
+                {
+                    return_errno_with_message!(
+                        Errno::EACCES,
+                        "the file is not owned by the caller"
+                    );
+                }
+
+                self.set_encryption_policy(&policy)?;
+                Ok(0)
+            }
+            IoctlCmd::FS_IOC_GET_ENCRYPTION_POLICY => {
+                let policy = self.encryption_policy()?;
+                current_userspace!().write_val(arg, &policy)?;
+                Ok(0)
+            }
+            _ => Err(Error::new(Errno::EINVAL)),
+        }
     }
 
     fn sync_all(&self) -> Result<()> {
@@ -191,6 +219,11 @@ impl Inode for Ext2Inode {
         self.fs()
     }
 
+    fn is_dentry_cacheable(&self) -> bool {
+        // The names of the encrypted inodes change when the key is added or removed.
+        !self.is_encrypted()
+    }
+
     fn extension(&self) -> Option<&Extension> {
         Some(self.extension())
     }
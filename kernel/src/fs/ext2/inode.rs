@@ -3,10 +3,12 @@
 #![allow(dead_code)]
 #![allow(unused_variables)]
 
-use alloc::{borrow::ToOwned, rc::Rc};
+use alloc::{borrow::Cow, rc::Rc};
 use core::sync::atomic::{AtomicUsize, Ordering};
 
 use inherit_methods_macro::inherit_methods;
+use ostd::{mm::MAX_USERSPACE_VADDR, task::Task};
+use spin::Once;
 
 use super::{
     block_ptr::{BidPath, BlockPtrs, Ext2Bid, BID_SIZE, MAX_BLOCK_PTRS},
@@ -15,10 +17,17 @@ use super::{
     indirect_block_cache::{IndirectBlock, IndirectBlockCache},
     prelude::*,
     utils::now,
+    xattr::{create_xattr_block, get_xattr, release_xattr_block, XattrName},
 };
 use crate::{
-    fs::utils::{Extension, FallocMode, InodeMode, Metadata},
-    process::{posix_thread::AsPosixThread, Gid, Uid},
+    fs::{
+        fscrypt::{encode_nokey_name, CryptInfo, EncryptionContext, EncryptionPolicy},
+        utils::{Extension, FallocMode, InodeMode, Metadata},
+    },
+    process::{
+        posix_thread::{AsPosixThread, AsThreadLocal},
+        Gid, Uid,
+    },
 };
 
 /// Max length of file name.
@@ -27,12 +36,21 @@ pub const MAX_FNAME_LEN: usize = 255;
 /// Max path length of the fast symlink.
 pub const MAX_FAST_SYMLINK_LEN: usize = MAX_BLOCK_PTRS * BID_SIZE;
 
+/// Max length of the target of an encrypted symlink.
+///
+/// The encrypted target is stored after its length, which is a 16-bit integer.
+const MAX_ENCRYPTED_SYMLINK_LEN: usize = BLOCK_SIZE - size_of::<u16>();
+
 /// The Ext2 inode.
 pub struct Inode {
     ino: u32,
     type_: InodeType,
     block_group_idx: usize,
     inner: RwMutex<InodeInner>,
+    /// The encryption information, which is set up on demand if the inode is encrypted.
+    ///
+    /// It is shared with the block manager to encrypt the contents of regular files.
+    crypt_info: Arc<Once<CryptInfo>>,
     fs: Weak<Ext2>,
     extension: Extension,
 }
@@ -44,11 +62,18 @@ impl Inode {
         desc: Dirty<InodeDesc>,
         fs: Weak<Ext2>,
     ) -> Arc<Self> {
+        let crypt_info = Arc::new(Once::new());
         Arc::new_cyclic(|weak_self| Self {
             ino,
             type_: desc.type_,
             block_group_idx,
-            inner: RwMutex::new(InodeInner::new(desc, weak_self.clone(), fs.clone())),
+            inner: RwMutex::new(InodeInner::new(
+                desc,
+                weak_self.clone(),
+                fs.clone(),
+                crypt_info.clone(),
+            )),
+            crypt_info,
             fs,
             extension: Extension::new(),
         })
@@ -98,6 +123,8 @@ impl Inode {
         if self.type_ != InodeType::File {
             return_errno!(Errno::EISDIR);
         }
+        // The key is required to zero the tail of the last block of an encrypted file.
+        self.crypt_info()?;
 
         let inner = self.inner.upread();
         if new_size == inner.file_size() {
@@ -120,18 +147,14 @@ impl Inode {
         inode_type: InodeType,
         file_perm: FilePerm,
     ) -> Result<Arc<Self>> {
-        if name.len() > MAX_FNAME_LEN {
-            return_errno!(Errno::ENAMETOOLONG);
-        }
-        if self.type_ != InodeType::Dir {
-            return_errno!(Errno::ENOTDIR);
-        }
+        let name = self.encrypt_name(name)?;
+        let crypt_info = self.crypt_info()?;
 
         let inner = self.inner.upread();
         if inner.hard_links() == 0 {
             return_errno_with_message!(Errno::ENOENT, "dir removed");
         }
-        if inner.contains_entry(name) {
+        if inner.contains_entry(&name) {
             return_errno!(Errno::EEXIST);
         }
 
@@ -139,14 +162,14 @@ impl Inode {
             .fs()
             .create_inode(self.block_group_idx, inode_type, file_perm)?;
         let is_dir = inode_type == InodeType::Dir;
-        if let Err(e) = inode.init(self.ino) {
+        if let Err(e) = inode.init(self.ino, crypt_info) {
             self.fs().free_inode(inode.ino, is_dir).unwrap();
             return Err(e);
         }
-        let new_entry = DirEntry::new(inode.ino, name, inode_type);
+        let new_entry = DirEntry::new(inode.ino, &name, inode_type);
 
         let mut inner = inner.upgrade();
-        if let Err(e) = inner.append_entry(new_entry, inode_type, name) {
+        if let Err(e) = inner.append_entry(new_entry, inode_type, &name) {
             self.fs().free_inode(inode.ino, is_dir).unwrap();
             return Err(e);
         }
@@ -157,7 +180,16 @@ impl Inode {
         Ok(inode)
     }
 
-    fn init(&self, dir_ino: u32) -> Result<()> {
+    fn init(&self, dir_ino: u32, dir_crypt_info: Option<&CryptInfo>) -> Result<()> {
+        // Like Linux, the new inode inherits the encryption policy of the directory, unless
+        // it is a special file.
+        if let Some(dir_crypt_info) = dir_crypt_info
+            && is_encryptable(self.type_)
+        {
+            let context = dir_crypt_info.context().new_child();
+            self.inner.write().set_encryption_context(&context)?;
+        }
+
         match self.type_ {
             InodeType::Dir => {
                 self.inner.write().init_dir(self.ino, dir_ino)?;
@@ -170,13 +202,12 @@ impl Inode {
     }
 
     pub fn lookup(&self, name: &str) -> Result<Arc<Self>> {
-        if name.len() > MAX_FNAME_LEN {
-            return_errno!(Errno::ENAMETOOLONG);
-        }
-        if self.type_ != InodeType::Dir {
-            return_errno!(Errno::ENOTDIR);
-        }
+        let name = self.resolve_name(name)?;
+        self.lookup_disk_name(&name)
+    }
 
+    /// Looks up an inode with its name stored on the device.
+    fn lookup_disk_name(&self, name: &[u8]) -> Result<Arc<Self>> {
         let inner = self.inner.read();
         if inner.hard_links() == 0 {
             return_errno_with_message!(Errno::ENOENT, "dir removed");
@@ -191,12 +222,8 @@ impl Inode {
     }
 
     pub fn link(&self, inode: &Inode, name: &str) -> Result<()> {
-        if name.len() > MAX_FNAME_LEN {
-            return_errno!(Errno::ENAMETOOLONG);
-        }
-        if self.type_ != InodeType::Dir {
-            return_errno!(Errno::ENOTDIR);
-        }
+        let name = self.encrypt_name(name)?;
+        self.check_permitted_context(inode)?;
 
         let inner = self.inner.upread();
         if inner.hard_links() == 0 {
@@ -208,13 +235,13 @@ impl Inode {
             return_errno!(Errno::EPERM);
         }
 
-        if inner.contains_entry(name) {
+        if inner.contains_entry(&name) {
             return_errno!(Errno::EEXIST);
         }
 
-        let new_entry = DirEntry::new(inode.ino, name, inode_type);
+        let new_entry = DirEntry::new(inode.ino, &name, inode_type);
         let mut inner = inner.upgrade();
-        inner.append_entry(new_entry, inode_type, name)?;
+        inner.append_entry(new_entry, inode_type, &name)?;
         let now = now();
         inner.set_mtime(now);
         inner.set_ctime(now);
@@ -232,7 +259,9 @@ impl Inode {
             return_errno!(Errno::EISDIR);
         }
 
-        let file = self.lookup(name)?;
+        let name_buf = self.resolve_name(name)?;
+        let name: &[u8] = &name_buf;
+        let file = self.lookup_disk_name(name)?;
         if file.inode_type() == InodeType::Dir {
             return_errno!(Errno::EISDIR);
         }
@@ -272,7 +301,9 @@ impl Inode {
             return_errno_with_message!(Errno::ENOTEMPTY, "rmdir on ..");
         }
 
-        let dir_inode = self.lookup(name)?;
+        let name_buf = self.resolve_name(name)?;
+        let name: &[u8] = &name_buf;
+        let dir_inode = self.lookup_disk_name(name)?;
         let dir_inner = dir_inode.inner.read();
         if dir_inner.inode_type() != InodeType::Dir {
             return_errno!(Errno::ENOTDIR);
@@ -316,11 +347,7 @@ impl Inode {
     }
 
     /// Rename within its own directory.
-    fn rename_within(&self, old_name: &str, new_name: &str) -> Result<()> {
-        if self.type_ != InodeType::Dir {
-            return_errno!(Errno::ENOTDIR);
-        }
-
+    fn rename_within(&self, old_name: &[u8], new_name: &[u8]) -> Result<()> {
         let self_inner = self.inner.upread();
         if self_inner.hard_links() == 0 {
             return_errno_with_message!(Errno::ENOENT, "dir removed");
@@ -428,11 +455,23 @@ impl Inode {
             return_errno!(Errno::ENAMETOOLONG);
         }
 
+        // Like Linux, the keys of both directories are required, even if the old name is a
+        // no-key name.
+        let old_name_buf = self.encrypt_name(old_name)?;
+        let old_name: &[u8] = &old_name_buf;
+        let new_name_buf = target.encrypt_name(new_name)?;
+        let new_name: &[u8] = &new_name_buf;
+
         // Rename inside the inode
         if self.ino == target.ino {
             return self.rename_within(old_name, new_name);
         }
 
+        if target.is_encrypted() {
+            let src_inode = self.lookup_disk_name(old_name)?;
+            target.check_permitted_context(&src_inode)?;
+        }
+
         let (self_inner, target_inner) = read_lock_two_inodes(self, target);
         if self_inner.inode_type() != InodeType::Dir || target_inner.inode_type() != InodeType::Dir
         {
@@ -604,6 +643,7 @@ impl Inode {
         if self.type_ != InodeType::Dir {
             return_errno!(Errno::ENOTDIR);
         }
+        let name_crypt = self.name_crypt()?;
 
         let offset_read = {
             let inner = self.inner.read();
@@ -615,7 +655,7 @@ impl Inode {
                 let mut dir_entry_reader = DirEntryReader::new(&inner.page_cache, *offset);
                 for dir_entry in dir_entry_reader.iter_entries() {
                     visitor.visit(
-                        dir_entry.name(),
+                        &name_crypt.decrypt_name(dir_entry.name())?,
                         dir_entry.ino() as u64,
                         dir_entry.type_(),
                         dir_entry.record_len(),
@@ -643,17 +683,40 @@ impl Inode {
             return_errno!(Errno::EISDIR);
         }
 
+        // The target of an encrypted symlink is stored after its length.
+        let target = match self.crypt_info()? {
+            Some(crypt_info) => {
+                let encrypted_target =
+                    crypt_info.encrypt_name(target.as_bytes(), MAX_ENCRYPTED_SYMLINK_LEN)?;
+                let mut disk_target = (encrypted_target.len() as u16).to_le_bytes().to_vec();
+                disk_target.extend_from_slice(&encrypted_target);
+                Cow::Owned(disk_target)
+            }
+            None => Cow::Borrowed(target.as_bytes()),
+        };
+
         let mut inner = self.inner.write();
-        inner.write_link(target)
+        inner.write_link(&target)
     }
 
     pub fn read_link(&self) -> Result<String> {
         if self.type_ != InodeType::SymLink {
             return_errno!(Errno::EISDIR);
         }
+        let name_crypt = self.name_crypt()?;
 
-        let inner = self.inner.read();
-        inner.read_link()
+        let disk_target = self.inner.read().read_link()?;
+        if let NameCrypt::Plain = name_crypt {
+            return Ok(String::from_utf8(disk_target)?);
+        }
+
+        let encrypted_target = disk_target
+            .split_first_chunk::<{ size_of::<u16>() }>()
+            .and_then(|(len, encrypted_target)| {
+                encrypted_target.get(..u16::from_le_bytes(*len) as usize)
+            })
+            .ok_or_else(|| Error::with_message(Errno::EUCLEAN, "the symlink is corrupted"))?;
+        name_crypt.decrypt_name(encrypted_target)
     }
 
     pub fn set_device_id(&self, device_id: u64) -> Result<()> {
@@ -687,16 +750,30 @@ impl Inode {
         Ok(bytes_read)
     }
 
-    // The offset and the length of buffer must be multiples of the block size.
+    // The offset, the address and the length of buffer must be multiples of the block size.
     pub fn read_direct_at(&self, offset: usize, writer: &mut VmWriter) -> Result<usize> {
         if self.type_ != InodeType::File {
             return_errno!(Errno::EISDIR);
         }
+        let buf_addr = writer.cursor() as Vaddr;
         if !is_block_aligned(offset) || !is_block_aligned(writer.avail()) {
             return_errno_with_message!(Errno::EINVAL, "not block-aligned");
         }
+        if is_user_buf(buf_addr) && !is_block_aligned(buf_addr) {
+            return_errno_with_message!(Errno::EINVAL, "the buffer is not block-aligned");
+        }
+        // Like Linux, the direct I/O of encrypted files falls back to the buffered I/O.
+        if self.is_encrypted() {
+            return self.read_at(offset, writer);
+        }
 
-        let bytes_read = self.inner.read().read_direct_at(offset, writer)?;
+        // The user pages are pinned before locking the inode, since the buffer may be mapped
+        // from this file.
+        let frames = pin_user_buf(buf_addr, writer.avail(), true)?;
+        let bytes_read = self
+            .inner
+            .read()
+            .read_direct_at(offset, writer, frames.as_deref())?;
 
         self.set_atime(now());
 
@@ -728,17 +805,25 @@ impl Inode {
         Ok(bytes_written)
     }
 
-    // The offset and the length of buffer must be multiples of the block size.
+    // The offset, the address and the length of buffer must be multiples of the block size.
     pub fn write_direct_at(&self, offset: usize, reader: &mut VmReader) -> Result<usize> {
         if self.type_ != InodeType::File {
             return_errno!(Errno::EISDIR);
         }
+        let buf_addr = reader.cursor() as Vaddr;
         if !is_block_aligned(offset) || !is_block_aligned(reader.remain()) {
             return_errno_with_message!(Errno::EINVAL, "not block aligned");
         }
+        if is_user_buf(buf_addr) && !is_block_aligned(buf_addr) {
+            return_errno_with_message!(Errno::EINVAL, "the buffer is not block-aligned");
+        }
+        if self.is_encrypted() {
+            return self.write_at(offset, reader);
+        }
 
+        let frames = pin_user_buf(buf_addr, reader.remain(), false)?;
         let mut inner = self.inner.write();
-        let bytes_written = inner.write_direct_at(offset, reader)?;
+        let bytes_written = inner.write_direct_at(offset, reader, frames.as_deref())?;
 
         let now = now();
         inner.set_mtime(now);
@@ -814,6 +899,186 @@ impl Inode {
             }
         }
     }
+
+    /// Sets the encryption policy of an empty directory.
+    ///
+    /// If the directory is already encrypted, this method succeeds only if the policy is the
+    /// same as the existing one.
+    pub fn set_encryption_policy(&self, policy: &EncryptionPolicy) -> Result<()> {
+        let mut inner = self.inner.write();
+        if inner.is_encrypted() {
+            if inner.encryption_context()?.policy() != *policy {
+                return_errno_with_message!(Errno::EEXIST, "the policy is different");
+            }
+            return Ok(());
+        }
+
+        if self.type_ != InodeType::Dir {
+            return_errno_with_message!(Errno::ENOTDIR, "only directories can be encrypted");
+        }
+        if inner.hard_links() == 0 {
+            return_errno_with_message!(Errno::ENOENT, "dir removed");
+        }
+        if inner.entry_count() > 2 {
+            return_errno_with_message!(Errno::ENOTEMPTY, "the directory is not empty");
+        }
+
+        let context = EncryptionContext::new(policy)?;
+        inner.set_encryption_context(&context)?;
+        inner.set_ctime(now());
+        Ok(())
+    }
+
+    /// Returns the encryption policy of an encrypted inode.
+    ///
+    /// This method fails with `ENODATA` if the inode is not encrypted.
+    pub fn encryption_policy(&self) -> Result<EncryptionPolicy> {
+        let inner = self.inner.read();
+        if !inner.is_encrypted() {
+            return_errno_with_message!(Errno::ENODATA, "the inode is not encrypted");
+        }
+        Ok(inner.encryption_context()?.policy())
+    }
+
+    /// Prepares the inode to be opened.
+    ///
+    /// Like Linux, an encrypted regular file cannot be opened without its key.
+    pub fn prepare_open(&self) -> Result<()> {
+        if self.type_ == InodeType::File {
+            self.crypt_info()?;
+        }
+        Ok(())
+    }
+
+    pub fn is_encrypted(&self) -> bool {
+        self.inner.read().is_encrypted()
+    }
+
+    /// Returns the encryption information if the inode is encrypted.
+    ///
+    /// The encryption information is set up at the first call, which fails with `ENOKEY` if
+    /// the master key is absent in the keyrings.
+    fn crypt_info(&self) -> Result<Option<&CryptInfo>> {
+        if let Some(crypt_info) = self.crypt_info.get() {
+            return Ok(Some(crypt_info));
+        }
+        if !self.is_encrypted() {
+            return Ok(None);
+        }
+
+        let crypt_info = self.crypt_info.try_call_once(|| {
+            let context = self.inner.read().encryption_context()?;
+            CryptInfo::new(context, self.type_)
+        })?;
+        Ok(Some(crypt_info))
+    }
+
+    /// Returns how the names in the directory, or the target of the symlink, are encrypted.
+    fn name_crypt(&self) -> Result<NameCrypt<'_>> {
+        match self.crypt_info() {
+            Ok(Some(crypt_info)) => Ok(NameCrypt::Encrypted(crypt_info)),
+            Ok(None) => Ok(NameCrypt::Plain),
+            Err(err) if err.error() == Errno::ENOKEY => Ok(NameCrypt::NoKey),
+            Err(err) => Err(err),
+        }
+    }
+
+    /// Converts a name in the directory to the name that is stored on the device.
+    ///
+    /// If the directory is encrypted but its key is absent, the name must be the no-key name
+    /// of an existing entry, which is presented by [`Self::readdir_at`].
+    fn resolve_name<'a>(&self, name: &'a str) -> Result<Cow<'a, [u8]>> {
+        if name.len() > MAX_FNAME_LEN {
+            return_errno!(Errno::ENAMETOOLONG);
+        }
+        if self.type_ != InodeType::Dir {
+            return_errno!(Errno::ENOTDIR);
+        }
+        if name == "." || name == ".." {
+            return Ok(Cow::Borrowed(name.as_bytes()));
+        }
+
+        match self.name_crypt()? {
+            NameCrypt::Plain => Ok(Cow::Borrowed(name.as_bytes())),
+            NameCrypt::Encrypted(crypt_info) => Ok(Cow::Owned(
+                crypt_info.encrypt_name(name.as_bytes(), MAX_FNAME_LEN)?,
+            )),
+            NameCrypt::NoKey => self
+                .inner
+                .read()
+                .find_nokey_name(name)
+                .map(Cow::Owned)
+                .ok_or_else(|| Error::new(Errno::ENOENT)),
+        }
+    }
+
+    /// Converts a name in the directory to the name that is stored on the device, which
+    /// requires the key if the directory is encrypted.
+    ///
+    /// Unlike [`Self::resolve_name`], this method fails with `ENOKEY` if the key is absent,
+    /// so it is used when a name is added to the directory.
+    fn encrypt_name<'a>(&self, name: &'a str) -> Result<Cow<'a, [u8]>> {
+        if name.len() > MAX_FNAME_LEN {
+            return_errno!(Errno::ENAMETOOLONG);
+        }
+        if self.type_ != InodeType::Dir {
+            return_errno!(Errno::ENOTDIR);
+        }
+
+        match self.crypt_info()? {
+            Some(crypt_info) if name != "." && name != ".." => Ok(Cow::Owned(
+                crypt_info.encrypt_name(name.as_bytes(), MAX_FNAME_LEN)?,
+            )),
+            _ => Ok(Cow::Borrowed(name.as_bytes())),
+        }
+    }
+
+    /// Checks whether the inode can be linked into the directory.
+    ///
+    /// Like Linux, the files in an encrypted directory must be encrypted with the same policy,
+    /// except the special files, which are never encrypted. Otherwise, this method fails with
+    /// `EXDEV`.
+    fn check_permitted_context(&self, inode: &Inode) -> Result<()> {
+        let Some(crypt_info) = self.crypt_info()? else {
+            return Ok(());
+        };
+        if !is_encryptable(inode.type_) {
+            return Ok(());
+        }
+
+        let inode_inner = inode.inner.read();
+        if !inode_inner.is_encrypted()
+            || inode_inner.encryption_context()?.policy() != crypt_info.context().policy()
+        {
+            return_errno_with_message!(Errno::EXDEV, "the encryption policy is different");
+        }
+        Ok(())
+    }
+}
+
+/// How the names in a directory, or the target of a symlink, are stored on the device.
+enum NameCrypt<'a> {
+    /// The names are not encrypted.
+    Plain,
+    /// The names are encrypted and the key is available.
+    Encrypted(&'a CryptInfo),
+    /// The names are encrypted but the key is absent.
+    NoKey,
+}
+
+impl NameCrypt<'_> {
+    /// Converts a name that is stored on the device to the name presented to the user space.
+    ///
+    /// Without the key, the encrypted names are presented as their no-key names.
+    fn decrypt_name(&self, disk_name: &[u8]) -> Result<String> {
+        let name = match self {
+            _ if disk_name == b"." || disk_name == b".." => disk_name.to_vec(),
+            Self::Plain => disk_name.to_vec(),
+            Self::Encrypted(crypt_info) => crypt_info.decrypt_name(disk_name)?,
+            Self::NoKey => return Ok(encode_nokey_name(disk_name)),
+        };
+        Ok(String::from_utf8(name)?)
+    }
 }
 
 #[inherit_methods(from = "self.inner.read()")]
@@ -908,9 +1173,14 @@ struct InodeInner {
 }
 
 impl InodeInner {
-    pub fn new(desc: Dirty<InodeDesc>, weak_self: Weak<Inode>, fs: Weak<Ext2>) -> Self {
+    pub fn new(
+        desc: Dirty<InodeDesc>,
+        weak_self: Weak<Inode>,
+        fs: Weak<Ext2>,
+        crypt_info: Arc<Once<CryptInfo>>,
+    ) -> Self {
         let num_page_bytes = desc.num_page_bytes();
-        let inode_impl = InodeImpl::new(desc, weak_self, fs);
+        let inode_impl = InodeImpl::new(desc, weak_self, fs, crypt_info);
         Self {
             page_cache: PageCache::with_capacity(
                 num_page_bytes,
@@ -939,25 +1209,40 @@ impl InodeInner {
         Ok(read_len)
     }
 
-    pub fn read_direct_at(&self, offset: usize, writer: &mut VmWriter) -> Result<usize> {
+    /// Reads the blocks from the device directly.
+    ///
+    /// If `frames` is provided, they are the pinned pages of the buffer of `writer`, and the
+    /// device writes to them without copying. Otherwise, the data is copied to `writer`.
+    pub fn read_direct_at(
+        &self,
+        offset: usize,
+        writer: &mut VmWriter,
+        frames: Option<&[UFrame]>,
+    ) -> Result<usize> {
         debug_assert!(is_block_aligned(offset) && is_block_aligned(writer.avail()));
-        let (offset, read_len) = {
-            let file_size = self.inode_impl.file_size();
-            let start = file_size.min(offset).align_down(BLOCK_SIZE);
-            let end = file_size
-                .min(offset + writer.avail())
-                .align_down(BLOCK_SIZE);
-            (start, end - start)
-        };
-        if read_len == 0 {
-            return Ok(read_len);
+        let file_size = self.inode_impl.file_size();
+        if offset >= file_size {
+            return Ok(0);
         }
-        self.page_cache.discard_range(offset..offset + read_len);
+
+        // The last block may be partially covered by the file. As Linux does, the whole
+        // block is read, but only the bytes within the file are counted.
+        let read_len = (file_size - offset).min(writer.avail());
+        let buf_nblocks = read_len.align_up(BLOCK_SIZE) / BLOCK_SIZE;
+
+        // Write back the dirty pages first, so that the device holds the latest data.
+        self.page_cache
+            .evict_range(offset..offset + buf_nblocks * BLOCK_SIZE)?;
 
         let start_bid = Bid::from_offset(offset).to_raw() as Ext2Bid;
-        let buf_nblocks = read_len / BLOCK_SIZE;
-        self.inode_impl
-            .read_blocks(start_bid, buf_nblocks, writer)?;
+        if let Some(frames) = frames {
+            self.inode_impl
+                .read_blocks_to_frames(start_bid, &frames[..buf_nblocks])?;
+            skip_writer(writer, buf_nblocks * BLOCK_SIZE);
+        } else {
+            self.inode_impl
+                .read_blocks(start_bid, buf_nblocks, writer)?;
+        }
 
         Ok(read_len)
     }
@@ -977,7 +1262,16 @@ impl InodeInner {
         Ok(write_len)
     }
 
-    pub fn write_direct_at(&mut self, offset: usize, reader: &mut VmReader) -> Result<usize> {
+    /// Writes the blocks to the device directly.
+    ///
+    /// If `frames` is provided, they are the pinned pages of the buffer of `reader`, and the
+    /// device reads from them without copying. Otherwise, the data is copied from `reader`.
+    pub fn write_direct_at(
+        &mut self,
+        offset: usize,
+        reader: &mut VmReader,
+        frames: Option<&[UFrame]>,
+    ) -> Result<usize> {
         debug_assert!(is_block_aligned(offset) && is_block_aligned(reader.remain()));
         let file_size = self.inode_impl.file_size();
         let write_len = reader.remain();
@@ -988,24 +1282,32 @@ impl InodeInner {
         self.page_cache.discard_range(start..end);
 
         if end_offset > file_size {
-            self.inode_impl.resize(end_offset)?;
+            // The page cache must cover the new size as well, otherwise the later
+            // buffered reads cannot see the data written here.
+            self.resize(end_offset)?;
         }
 
         let start_bid = Bid::from_offset(offset).to_raw() as Ext2Bid;
         let buf_nblocks = write_len / BLOCK_SIZE;
-        self.inode_impl
-            .write_blocks(start_bid, buf_nblocks, reader)?;
+        if let Some(frames) = frames {
+            self.inode_impl
+                .write_blocks_from_frames(start_bid, &frames[..buf_nblocks])?;
+            skip_reader(reader, write_len);
+        } else {
+            self.inode_impl
+                .write_blocks(start_bid, buf_nblocks, reader)?;
+        }
 
         Ok(write_len)
     }
 
-    pub fn write_link(&mut self, target: &str) -> Result<()> {
+    pub fn write_link(&mut self, target: &[u8]) -> Result<()> {
         if target.len() <= MAX_FAST_SYMLINK_LEN {
             return self.inode_impl.write_link(target);
         }
 
         self.page_cache.resize(target.len())?;
-        self.page_cache.pages().write_bytes(0, target.as_bytes())?;
+        self.page_cache.pages().write_bytes(0, target)?;
         let file_size = self.inode_impl.file_size();
         if file_size != target.len() {
             self.inode_impl.resize(target.len())?;
@@ -1013,10 +1315,10 @@ impl InodeInner {
         Ok(())
     }
 
-    pub fn read_link(&self) -> Result<String> {
+    pub fn read_link(&self) -> Result<Vec<u8>> {
         let file_size = self.inode_impl.file_size();
         if file_size <= MAX_FAST_SYMLINK_LEN {
-            return self.inode_impl.read_link();
+            return Ok(self.inode_impl.read_link());
         }
 
         let mut symlink = vec![0u8; file_size];
@@ -1024,24 +1326,33 @@ impl InodeInner {
             .pages()
             .read_bytes(0, symlink.as_mut_slice())?;
 
-        Ok(String::from_utf8(symlink)?)
+        Ok(symlink)
     }
 
     fn init_dir(&mut self, self_ino: u32, parent_ino: u32) -> Result<()> {
         debug_assert_eq!(self.inode_type(), InodeType::Dir);
-        self.append_entry(DirEntry::self_entry(self_ino), InodeType::Dir, ".")?;
-        self.append_entry(DirEntry::parent_entry(parent_ino), InodeType::Dir, "..")?;
+        self.append_entry(DirEntry::self_entry(self_ino), InodeType::Dir, b".")?;
+        self.append_entry(DirEntry::parent_entry(parent_ino), InodeType::Dir, b"..")?;
         Ok(())
     }
 
-    pub fn contains_entry(&self, name: &str) -> bool {
+    pub fn contains_entry(&self, name: &[u8]) -> bool {
         DirEntryReader::new(&self.page_cache, 0).contains_entry(name)
     }
 
-    pub fn find_entry_item(&self, name: &str) -> Option<DirEntryItem> {
+    pub fn find_entry_item(&self, name: &[u8]) -> Option<DirEntryItem> {
         DirEntryReader::new(&self.page_cache, 0).find_entry_item(name)
     }
 
+    /// Finds the name of the entry whose no-key name is `nokey_name`.
+    pub fn find_nokey_name(&self, nokey_name: &str) -> Option<Vec<u8>> {
+        let mut dir_entry_reader = DirEntryReader::new(&self.page_cache, 0);
+        let dir_entry = dir_entry_reader
+            .iter_entries()
+            .find(|dir_entry| encode_nokey_name(dir_entry.name()) == nokey_name)?;
+        Some(dir_entry.name().to_vec())
+    }
+
     pub fn entry_count(&self) -> usize {
         DirEntryReader::new(&self.page_cache, 0).entry_count()
     }
@@ -1050,7 +1361,7 @@ impl InodeInner {
         &mut self,
         entry: DirEntry,
         inode_type: InodeType,
-        name: &str,
+        name: &[u8],
     ) -> Result<()> {
         debug_assert!(inode_type == entry.type_() && entry.name() == name);
 
@@ -1062,14 +1373,14 @@ impl InodeInner {
         }
 
         let is_dir = inode_type == InodeType::Dir;
-        let is_parent = name == "..";
+        let is_parent = name == b"..";
         if is_dir && !is_parent {
             self.inc_hard_links(); // for ".."
         }
         Ok(())
     }
 
-    pub fn remove_entry_at(&mut self, name: &str, offset: usize) -> Result<()> {
+    pub fn remove_entry_at(&mut self, name: &[u8], offset: usize) -> Result<()> {
         let entry = DirEntryWriter::new(&self.page_cache, offset).remove_entry(name)?;
         let is_dir = entry.type_() == InodeType::Dir;
         let file_size = self.file_size();
@@ -1083,7 +1394,12 @@ impl InodeInner {
         Ok(())
     }
 
-    pub fn rename_entry_at(&mut self, old_name: &str, new_name: &str, offset: usize) -> Result<()> {
+    pub fn rename_entry_at(
+        &mut self,
+        old_name: &[u8],
+        new_name: &[u8],
+        offset: usize,
+    ) -> Result<()> {
         DirEntryWriter::new(&self.page_cache, offset).rename_entry(old_name, new_name)?;
         let file_size = self.file_size();
         let page_cache_size = self.page_cache.pages().size();
@@ -1094,7 +1410,7 @@ impl InodeInner {
     }
 
     pub fn set_parent_ino(&mut self, parent_ino: u32) -> Result<()> {
-        let mut entry_item = self.find_entry_item("..").unwrap();
+        let mut entry_item = self.find_entry_item(b"..").unwrap();
         entry_item.set_ino(parent_ino);
         DirEntryWriter::new(&self.page_cache, entry_item.offset())
             .write_header_only(entry_item.header())?;
@@ -1107,6 +1423,41 @@ impl InodeInner {
         self.page_cache.evict_range(0..file_size)?;
         Ok(())
     }
+
+    pub fn is_encrypted(&self) -> bool {
+        self.file_flags().contains(FileFlags::ENCRYPT)
+    }
+
+    /// Reads the encryption context from the extended attributes.
+    pub fn encryption_context(&self) -> Result<EncryptionContext> {
+        let fs = self.inode_impl.fs();
+        let value = get_xattr(&fs, self.xattr_bid(), XattrName::ENCRYPTION_CONTEXT)?;
+        EncryptionContext::parse(&value)
+    }
+
+    /// Writes the encryption context to the extended attributes and marks the inode as
+    /// encrypted.
+    pub fn set_encryption_context(&mut self, context: &EncryptionContext) -> Result<()> {
+        debug_assert!(!self.is_encrypted());
+        // TODO: Support adding the encryption context to the existing extended attributes.
+        if self.xattr_bid() != 0 {
+            return_errno_with_message!(
+                Errno::EOPNOTSUPP,
+                "the inode already has extended attributes"
+            );
+        }
+
+        let inode = self.inode_impl.inode();
+        let xattr_bid = create_xattr_block(
+            &inode.fs(),
+            inode.block_group_idx(),
+            XattrName::ENCRYPTION_CONTEXT,
+            context.as_bytes(),
+        )?;
+        self.set_xattr_bid(xattr_bid);
+        self.set_file_flags(self.file_flags() | FileFlags::ENCRYPT);
+        Ok(())
+    }
 }
 
 #[inherit_methods(from = "self.inode_impl")]
@@ -1120,6 +1471,9 @@ impl InodeInner {
     pub fn gid(&self) -> u32;
     pub fn set_gid(&mut self, gid: u32);
     pub fn file_flags(&self) -> FileFlags;
+    pub fn set_file_flags(&mut self, flags: FileFlags);
+    pub fn xattr_bid(&self) -> Ext2Bid;
+    pub fn set_xattr_bid(&mut self, bid: Ext2Bid);
     pub fn hard_links(&self) -> u16;
     pub fn inc_hard_links(&mut self);
     pub fn dec_hard_links(&mut self);
@@ -1145,11 +1499,17 @@ struct InodeImpl {
 }
 
 impl InodeImpl {
-    pub fn new(desc: Dirty<InodeDesc>, weak_self: Weak<Inode>, fs: Weak<Ext2>) -> Self {
+    pub fn new(
+        desc: Dirty<InodeDesc>,
+        weak_self: Weak<Inode>,
+        fs: Weak<Ext2>,
+        crypt_info: Arc<Once<CryptInfo>>,
+    ) -> Self {
         let block_manager = InodeBlockManager {
             nblocks: AtomicUsize::new(desc.blocks_count() as _),
             block_ptrs: RwMutex::new(desc.block_ptrs),
             indirect_blocks: RwMutex::new(IndirectBlockCache::new(fs.clone())),
+            crypt_info,
             fs,
         };
         Self {
@@ -1205,6 +1565,18 @@ impl InodeImpl {
         self.desc.flags
     }
 
+    pub fn set_file_flags(&mut self, flags: FileFlags) {
+        self.desc.flags = flags;
+    }
+
+    pub fn xattr_bid(&self) -> Ext2Bid {
+        self.desc.xattr_bid
+    }
+
+    pub fn set_xattr_bid(&mut self, bid: Ext2Bid) {
+        self.desc.xattr_bid = bid;
+    }
+
     pub fn hard_links(&self) -> u16 {
         self.desc.hard_links
     }
@@ -1265,16 +1637,14 @@ impl InodeImpl {
         device_id
     }
 
-    pub fn read_link(&self) -> Result<String> {
-        let symlink_str = core::str::from_utf8(&self.desc.block_ptrs.as_bytes()[..self.desc.size])?;
-        Ok(symlink_str.to_owned())
+    pub fn read_link(&self) -> Vec<u8> {
+        self.desc.block_ptrs.as_bytes()[..self.desc.size].to_vec()
     }
 
-    pub fn write_link(&mut self, target: &str) -> Result<()> {
+    pub fn write_link(&mut self, target: &[u8]) -> Result<()> {
         let target_len = target.len();
-        self.desc.block_ptrs.as_bytes_mut()[..target_len].copy_from_slice(target.as_bytes());
-        self.block_manager.block_ptrs.write().as_bytes_mut()[..target_len]
-            .copy_from_slice(target.as_bytes());
+        self.desc.block_ptrs.as_bytes_mut()[..target_len].copy_from_slice(target);
+        self.block_manager.block_ptrs.write().as_bytes_mut()[..target_len].copy_from_slice(target);
         if self.desc.size != target_len {
             self.resize(target_len)?;
         }
@@ -1291,6 +1661,10 @@ impl InodeImpl {
             self.resize(0)?;
             // Adds the check here to prevent double-free.
             if !self.is_freed {
+                if self.desc.xattr_bid != 0 {
+                    release_xattr_block(&inode.fs(), self.desc.xattr_bid)?;
+                    self.desc.xattr_bid = 0;
+                }
                 inode
                     .fs()
                     .free_inode(inode.ino(), self.desc.type_ == InodeType::Dir)?;
@@ -1744,13 +2118,8 @@ impl InodeImpl {
 
 #[inherit_methods(from = "self.block_manager")]
 impl InodeImpl {
-    pub fn read_blocks_async(
-        &self,
-        bid: Ext2Bid,
-        nblocks: usize,
-        writer: &mut VmWriter,
-    ) -> Result<BioWaiter>;
     pub fn read_blocks(&self, bid: Ext2Bid, nblocks: usize, writer: &mut VmWriter) -> Result<()>;
+    pub fn read_blocks_to_frames(&self, bid: Ext2Bid, frames: &[UFrame]) -> Result<()>;
     pub fn read_block_async(&self, bid: Ext2Bid, frame: &CachePage) -> Result<BioWaiter>;
     pub fn write_blocks_async(
         &self,
@@ -1759,6 +2128,7 @@ impl InodeImpl {
         reader: &mut VmReader,
     ) -> Result<BioWaiter>;
     pub fn write_blocks(&self, bid: Ext2Bid, nblocks: usize, reader: &mut VmReader) -> Result<()>;
+    pub fn write_blocks_from_frames(&self, bid: Ext2Bid, frames: &[UFrame]) -> Result<()>;
     pub fn write_block_async(&self, bid: Ext2Bid, frame: &CachePage) -> Result<BioWaiter>;
 }
 
@@ -1772,39 +2142,49 @@ struct InodeBlockManager {
     /// frequent reads access the `InodeDesc` copy without locking.
     block_ptrs: RwMutex<BlockPtrs>,
     indirect_blocks: RwMutex<IndirectBlockCache>,
+    /// The encryption information of the inode, which encrypts the blocks of an encrypted
+    /// regular file.
+    crypt_info: Arc<Once<CryptInfo>>,
     fs: Weak<Ext2>,
 }
 
 impl InodeBlockManager {
-    /// Reads one or multiple blocks to the segment start from `bid` asynchronously.
-    pub fn read_blocks_async(
-        &self,
-        bid: Ext2Bid,
-        nblocks: usize,
-        writer: &mut VmWriter,
-    ) -> Result<BioWaiter> {
+    /// Reads one or multiple blocks start from `bid` to the `writer`.
+    ///
+    /// All the block I/O requests are submitted before waiting for any of them,
+    /// and the data is copied to the `writer` only after all of them complete.
+    pub fn read_blocks(&self, bid: Ext2Bid, nblocks: usize, writer: &mut VmWriter) -> Result<()> {
         debug_assert!(nblocks * BLOCK_SIZE <= writer.avail());
         let mut bio_waiter = BioWaiter::new();
+        let mut bio_segments = Vec::new();
 
         for dev_range in DeviceRangeReader::new(self, bid..bid + nblocks as Ext2Bid)? {
             let start_bid = dev_range.start as Ext2Bid;
             let range_nblocks = dev_range.len();
 
             let bio_segment = BioSegment::alloc(range_nblocks, BioDirection::FromDevice);
-            bio_segment.reader().unwrap().read_fallible(writer)?;
-
-            let waiter = self.fs().read_blocks_async(start_bid, bio_segment)?;
+            let waiter = self
+                .fs()
+                .read_blocks_async(start_bid, bio_segment.clone())?;
             bio_waiter.concat(waiter);
+            bio_segments.push(bio_segment);
         }
 
-        Ok(bio_waiter)
-    }
+        if !matches!(bio_waiter.wait(), Some(BioStatus::Complete)) {
+            return_errno!(Errno::EIO);
+        }
 
-    pub fn read_blocks(&self, bid: Ext2Bid, nblocks: usize, writer: &mut VmWriter) -> Result<()> {
-        match self.read_blocks_async(bid, nblocks, writer)?.wait() {
-            Some(BioStatus::Complete) => Ok(()),
-            _ => return_errno!(Errno::EIO),
+        for bio_segment in bio_segments {
+            bio_segment.reader().unwrap().read_fallible(writer)?;
         }
+        Ok(())
+    }
+
+    /// Reads one or multiple blocks start from `bid` to the `frames`.
+    ///
+    /// Each frame receives one block, so the data is not copied.
+    pub fn read_blocks_to_frames(&self, bid: Ext2Bid, frames: &[UFrame]) -> Result<()> {
+        self.transfer_frames(bid, frames, BioDirection::FromDevice)
     }
 
     pub fn read_block_async(&self, bid: Ext2Bid, frame: &CachePage) -> Result<BioWaiter> {
@@ -1854,6 +2234,44 @@ impl InodeBlockManager {
         }
     }
 
+    /// Writes one or multiple blocks start from `bid` from the `frames`.
+    ///
+    /// Each frame provides one block, so the data is not copied.
+    pub fn write_blocks_from_frames(&self, bid: Ext2Bid, frames: &[UFrame]) -> Result<()> {
+        self.transfer_frames(bid, frames, BioDirection::ToDevice)
+    }
+
+    /// Transfers the blocks start from `bid` between the device and the `frames` synchronously.
+    fn transfer_frames(
+        &self,
+        bid: Ext2Bid,
+        frames: &[UFrame],
+        direction: BioDirection,
+    ) -> Result<()> {
+        let mut bio_waiter = BioWaiter::new();
+        let nblocks = frames.len() as Ext2Bid;
+        let mut frames = frames.iter();
+
+        for dev_range in DeviceRangeReader::new(self, bid..bid + nblocks)? {
+            for (dev_bid, frame) in dev_range.zip(frames.by_ref()) {
+                let bio_segment =
+                    BioSegment::new_from_segment(USegment::from(frame.clone()), direction);
+                let waiter = match direction {
+                    BioDirection::FromDevice => {
+                        self.fs().read_blocks_async(dev_bid, bio_segment)?
+                    }
+                    BioDirection::ToDevice => self.fs().write_blocks_async(dev_bid, bio_segment)?,
+                };
+                bio_waiter.concat(waiter);
+            }
+        }
+
+        match bio_waiter.wait() {
+            Some(BioStatus::Complete) => Ok(()),
+            _ => return_errno!(Errno::EIO),
+        }
+    }
+
     pub fn write_block_async(&self, bid: Ext2Bid, frame: &CachePage) -> Result<BioWaiter> {
         let mut bio_waiter = BioWaiter::new();
 
@@ -1870,6 +2288,48 @@ impl InodeBlockManager {
         Ok(bio_waiter)
     }
 
+    /// Reads and decrypts a block of an encrypted regular file.
+    ///
+    /// The block is read synchronously, so the returned waiter has no requests.
+    pub fn read_encrypted_block(
+        &self,
+        bid: Ext2Bid,
+        frame: &CachePage,
+        crypt_info: &CryptInfo,
+    ) -> Result<BioWaiter> {
+        let mut block = vec![0u8; BLOCK_SIZE];
+        self.read_blocks(
+            bid,
+            1,
+            &mut VmWriter::from(block.as_mut_slice()).to_fallible(),
+        )?;
+        crypt_info.decrypt_block(bid as u64, &mut block);
+        frame.write_bytes(0, &block)?;
+        Ok(BioWaiter::new())
+    }
+
+    /// Encrypts and writes a block of an encrypted regular file asynchronously.
+    ///
+    /// The block is encrypted into a bounce buffer, so the page cache is kept in plaintext.
+    pub fn write_encrypted_block_async(
+        &self,
+        bid: Ext2Bid,
+        frame: &CachePage,
+        crypt_info: &CryptInfo,
+    ) -> Result<BioWaiter> {
+        let mut block = vec![0u8; BLOCK_SIZE];
+        frame.read_bytes(0, &mut block)?;
+        crypt_info.encrypt_block(bid as u64, &mut block);
+        self.write_blocks_async(bid, 1, &mut VmReader::from(block.as_slice()).to_fallible())
+    }
+
+    /// Returns the encryption information if the contents are encrypted.
+    fn contents_crypt_info(&self) -> Option<&CryptInfo> {
+        self.crypt_info
+            .get()
+            .filter(|crypt_info| crypt_info.encrypts_contents())
+    }
+
     pub fn nblocks(&self) -> usize {
         self.nblocks.load(Ordering::Acquire)
     }
@@ -1882,17 +2342,45 @@ impl InodeBlockManager {
 impl PageCacheBackend for InodeBlockManager {
     fn read_page_async(&self, idx: usize, frame: &CachePage) -> Result<BioWaiter> {
         let bid = idx as Ext2Bid;
+        if let Some(crypt_info) = self.contents_crypt_info() {
+            return self.read_encrypted_block(bid, frame, crypt_info);
+        }
         self.read_block_async(bid, frame)
     }
 
     fn write_page_async(&self, idx: usize, frame: &CachePage) -> Result<BioWaiter> {
         let bid = idx as Ext2Bid;
+        if let Some(crypt_info) = self.contents_crypt_info() {
+            return self.write_encrypted_block_async(bid, frame, crypt_info);
+        }
         self.write_block_async(bid, frame)
     }
 
     fn npages(&self) -> usize {
         self.nblocks()
     }
+
+    fn direct_access(&self, idx: usize) -> Option<UFrame> {
+        // The device holds the ciphertext of an encrypted regular file.
+        if idx >= self.nblocks() || self.contents_crypt_info().is_some() {
+            return None;
+        }
+
+        let bid = idx as Ext2Bid;
+        let device_range = DeviceRangeReader::new(self, bid..bid + 1 as Ext2Bid)
+            .ok()?
+            .next()?;
+        self.fs()
+            .block_device()
+            .direct_access(Bid::new(device_range.start as u64))
+    }
+
+    fn flush_direct(&self) -> Result<()> {
+        match self.fs().block_device().sync()? {
+            BioStatus::Complete => Ok(()),
+            _ => return_errno!(Errno::EIO),
+        }
+    }
 }
 
 /// A reader to get the corresponding device block IDs for a specified range.
@@ -2059,6 +2547,8 @@ pub(super) struct InodeDesc {
     block_ptrs: BlockPtrs,
     /// File or directory acl block.
     acl: Option<Bid>,
+    /// The block of the extended attributes.
+    xattr_bid: Ext2Bid,
 }
 
 impl TryFrom<RawInode> for InodeDesc {
@@ -2090,6 +2580,7 @@ impl TryFrom<RawInode> for InodeDesc {
                 InodeType::Dir => Some(Bid::new(inode.size_high as _)),
                 _ => None,
             },
+            xattr_bid: inode.file_acl,
         })
     }
 }
@@ -2116,6 +2607,7 @@ impl InodeDesc {
                 InodeType::File | InodeType::Dir => Some(Bid::new(0)),
                 _ => None,
             },
+            xattr_bid: 0,
         })
     }
 
@@ -2281,10 +2773,7 @@ impl From<&InodeDesc> for RawInode {
             blocks_count: inode.blocks_count,
             flags: inode.flags.bits(),
             block_ptrs: inode.block_ptrs,
-            file_acl: match inode.acl {
-                Some(acl) if inode.type_ == InodeType::File => acl.to_raw() as u32,
-                _ => Default::default(),
-            },
+            file_acl: inode.xattr_bid,
             size_high: match inode.acl {
                 Some(acl) if inode.type_ == InodeType::Dir => acl.to_raw() as u32,
                 _ => Default::default(),
@@ -2318,3 +2807,51 @@ pub(super) struct Osd2 {
 fn is_block_aligned(offset: usize) -> bool {
     offset % BLOCK_SIZE == 0
 }
+
+/// Returns whether the buffer of direct I/O is in the user space.
+///
+/// The buffers in the kernel space (e.g., those of io_uring) are accessed by copying.
+fn is_user_buf(addr: Vaddr) -> bool {
+    addr < MAX_USERSPACE_VADDR
+}
+
+/// Pins the pages of the user buffer of direct I/O, so that the device can access them directly.
+///
+/// Each page holds one block. Returns `None` if the buffer is not in the user space.
+fn pin_user_buf(addr: Vaddr, len: usize, is_write: bool) -> Result<Option<Vec<UFrame>>> {
+    const_assert!(BLOCK_SIZE == PAGE_SIZE);
+
+    if !is_user_buf(addr) {
+        return Ok(None);
+    }
+    let Some(end) = addr.checked_add(len) else {
+        return_errno_with_message!(Errno::EFAULT, "the buffer is not in the user space");
+    };
+
+    let current = Task::current().unwrap();
+    let Some(thread_local) = current.as_thread_local() else {
+        return_errno_with_message!(Errno::EFAULT, "the current thread has no user space");
+    };
+    let frames = thread_local.root_vmar().pin_pages(addr..end, is_write)?;
+    Ok(Some(frames))
+}
+
+/// Advances the writer whose buffer is written by the device.
+fn skip_writer(writer: &mut VmWriter, nbytes: usize) {
+    let empty_writer = VmWriter::from(&mut [] as &mut [u8]).to_fallible();
+    *writer = core::mem::replace(writer, empty_writer).skip(nbytes);
+}
+
+/// Advances the reader whose buffer is read by the device.
+fn skip_reader(reader: &mut VmReader, nbytes: usize) {
+    let empty_reader = VmReader::from(&[] as &[u8]).to_fallible();
+    *reader = core::mem::replace(reader, empty_reader).skip(nbytes);
+}
+
+/// Returns whether the inodes of the type can be encrypted.
+fn is_encryptable(inode_type: InodeType) -> bool {
+    matches!(
+        inode_type,
+        InodeType::File | InodeType::Dir | InodeType::SymLink
+    )
+}
@@ -0,0 +1,194 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! The extended attributes that are stored in a separate block of the inode.
+//!
+//! Currently, the extended attributes are only used to store the encryption contexts of the
+//! encrypted inodes, so only a block with a single attribute can be created.
+
+use super::{block_ptr::Ext2Bid, fs::Ext2, prelude::*};
+
+/// The magic number of the blocks of the extended attributes.
+const XATTR_MAGIC: u32 = 0xEA02_0000;
+
+/// The name of an extended attribute, which consists of the index of its prefix and the
+/// remaining part.
+#[derive(Clone, Copy, Debug)]
+pub(super) struct XattrName {
+    index: u8,
+    name: &'static [u8],
+}
+
+impl XattrName {
+    /// The name of the encryption context, whose index is `EXT4_XATTR_INDEX_ENCRYPTION`.
+    pub(super) const ENCRYPTION_CONTEXT: Self = Self {
+        index: 9,
+        name: b"c",
+    };
+}
+
+/// The header of a block of the extended attributes.
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Pod)]
+struct XattrHeader {
+    magic: u32,
+    /// The number of the inodes that share the block.
+    refcount: u32,
+    /// The number of the blocks, which is always one.
+    blocks: u32,
+    hash: u32,
+    reserved: [u32; 4],
+}
+
+/// The header of an entry in a block of the extended attributes.
+///
+/// The entries follow the header of the block, and each of them is followed by its name,
+/// which is padded to a multiple of 4 bytes. The entries end with 4 zero bytes. The values
+/// are stored at the end of the block.
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Pod)]
+struct XattrEntryHeader {
+    name_len: u8,
+    name_index: u8,
+    value_offs: u16,
+    value_block: u32,
+    value_size: u32,
+    hash: u32,
+}
+
+/// Reads the value of an extended attribute from the block.
+///
+/// This method fails with `ENODATA` if the attribute is absent.
+pub(super) fn get_xattr(fs: &Ext2, bid: Ext2Bid, name: XattrName) -> Result<Vec<u8>> {
+    if bid == 0 {
+        return_errno_with_message!(Errno::ENODATA, "the inode has no extended attributes");
+    }
+    let block = read_block(fs, bid)?;
+
+    let mut offset = size_of::<XattrHeader>();
+    while offset + size_of::<u32>() <= BLOCK_SIZE
+        && block[offset..offset + size_of::<u32>()] != [0; 4]
+    {
+        let name_offset = offset + size_of::<XattrEntryHeader>();
+        if name_offset > BLOCK_SIZE {
+            break;
+        }
+        let entry = XattrEntryHeader::from_bytes(&block[offset..name_offset]);
+        let name_end = name_offset + entry.name_len as usize;
+        if name_end > BLOCK_SIZE {
+            break;
+        }
+
+        if entry.name_index == name.index && &block[name_offset..name_end] == name.name {
+            let value_start = entry.value_offs as usize;
+            let value_end = value_start + entry.value_size as usize;
+            if entry.value_block != 0 || value_end > BLOCK_SIZE {
+                return_errno_with_message!(Errno::EUCLEAN, "the extended attribute is corrupted");
+            }
+            return Ok(block[value_start..value_end].to_vec());
+        }
+
+        offset = name_end.align_up(4);
+    }
+
+    return_errno_with_message!(Errno::ENODATA, "the extended attribute is not found");
+}
+
+/// Creates a block that holds a single extended attribute and returns its block ID.
+pub(super) fn create_xattr_block(
+    fs: &Ext2,
+    block_group_idx: usize,
+    name: XattrName,
+    value: &[u8],
+) -> Result<Ext2Bid> {
+    let mut block = vec![0u8; BLOCK_SIZE];
+
+    let name_offset = size_of::<XattrHeader>() + size_of::<XattrEntryHeader>();
+    let value_offs = BLOCK_SIZE - value.len().align_up(4);
+    block[name_offset..name_offset + name.name.len()].copy_from_slice(name.name);
+    block[value_offs..value_offs + value.len()].copy_from_slice(value);
+
+    let entry_hash = entry_hash(name.name, &block[value_offs..]);
+    let entry = XattrEntryHeader {
+        name_len: name.name.len() as u8,
+        name_index: name.index,
+        value_offs: value_offs as u16,
+        value_block: 0,
+        value_size: value.len() as u32,
+        hash: entry_hash,
+    };
+    block[size_of::<XattrHeader>()..name_offset].copy_from_slice(entry.as_bytes());
+
+    let header = XattrHeader {
+        magic: XATTR_MAGIC,
+        refcount: 1,
+        blocks: 1,
+        hash: block_hash(&[entry_hash]),
+        reserved: [0; 4],
+    };
+    block[..size_of::<XattrHeader>()].copy_from_slice(header.as_bytes());
+
+    let bid = fs
+        .alloc_blocks(block_group_idx, 1)
+        .ok_or_else(|| Error::with_message(Errno::ENOSPC, "no space for extended attributes"))?
+        .start;
+    if let Err(err) = write_block(fs, bid, &block) {
+        fs.free_blocks(bid..bid + 1).unwrap();
+        return Err(err);
+    }
+    Ok(bid)
+}
+
+/// Releases the reference of an inode to the block, which is freed if it is not shared.
+pub(super) fn release_xattr_block(fs: &Ext2, bid: Ext2Bid) -> Result<()> {
+    let block = read_block(fs, bid)?;
+    let mut header = XattrHeader::from_bytes(&block);
+    if header.magic != XATTR_MAGIC {
+        return_errno_with_message!(Errno::EUCLEAN, "the extended attribute block is corrupted");
+    }
+
+    if header.refcount > 1 {
+        header.refcount -= 1;
+        fs.block_device()
+            .write_val(Bid::new(bid as u64).to_offset(), &header)?;
+        return Ok(());
+    }
+    fs.free_blocks(bid..bid + 1)
+}
+
+fn read_block(fs: &Ext2, bid: Ext2Bid) -> Result<Vec<u8>> {
+    let mut block = vec![0u8; BLOCK_SIZE];
+    fs.block_device()
+        .read_bytes(Bid::new(bid as u64).to_offset(), &mut block)?;
+
+    let header = XattrHeader::from_bytes(&block);
+    if header.magic != XATTR_MAGIC || header.blocks != 1 {
+        return_errno_with_message!(Errno::EUCLEAN, "the extended attribute block is corrupted");
+    }
+    Ok(block)
+}
+
+fn write_block(fs: &Ext2, bid: Ext2Bid, block: &[u8]) -> Result<()> {
+    fs.block_device()
+        .write_bytes(Bid::new(bid as u64).to_offset(), block)?;
+    Ok(())
+}
+
+/// Computes the hash of an entry from its name and its value padded with zeros.
+fn entry_hash(name: &[u8], padded_value: &[u8]) -> u32 {
+    let hash = name.iter().fold(0u32, |hash, byte| {
+        (hash << 5) ^ (hash >> 27) ^ (*byte as i8 as u32)
+    });
+    padded_value.chunks_exact(4).fold(hash, |hash, word| {
+        (hash << 16) ^ (hash >> 16) ^ u32::from_le_bytes(word.try_into().unwrap())
+    })
+}
+
+/// Computes the hash of a block from the hashes of its entries.
+fn block_hash(entry_hashes: &[u32]) -> u32 {
+    if entry_hashes.contains(&0) {
+        return 0;
+    }
+    entry_hashes.iter().fold(0, |hash, entry_hash| {
+        (hash << 16) ^ (hash >> 16) ^ entry_hash
+    })
+}
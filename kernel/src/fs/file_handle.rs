@@ -4,12 +4,15 @@
 
 //! Opened File Handle
 
+use aster_rights::Rights;
+
 use super::inode_handle::InodeHandle;
 use crate::{
     fs::utils::{AccessMode, FallocMode, InodeMode, IoctlCmd, Metadata, SeekFrom, StatusFlags},
     net::socket::Socket,
     prelude::*,
     process::{signal::Pollable, Gid, Uid},
+    vm::vmo::Vmo,
 };
 
 /// The basic operations defined on a file
@@ -101,6 +104,14 @@ pub trait FileLike: Pollable + Send + Sync + Any {
     fn as_socket(&self) -> Option<&dyn Socket> {
         None
     }
+
+    /// Gets the VMO to be mapped by `mmap` at the given offset of the file.
+    ///
+    /// Returns the VMO and the corresponding offset in the VMO. Files backed by inodes are
+    /// mapped through their page caches, so this is only used by other kinds of files.
+    fn mmap_vmo(&self, offset: usize) -> Result<(Vmo<Rights>, usize)> {
+        return_errno_with_message!(Errno::ENODEV, "mmap is not supported");
+    }
 }
 
 impl dyn FileLike {
@@ -16,10 +16,11 @@ use super::{
 use crate::{
     events::{Events, IoEvents, Observer, Subject},
     fs::utils::StatusFlags,
+    ipc::mqueue::MessageQueue,
     prelude::*,
     process::{
         signal::{constants::SIGIO, signals::kernel::KernelSignal, PollAdaptor},
-        Pid, Process,
+        Pid, Process, ResourceType,
     },
 };
 
@@ -74,37 +75,55 @@ impl FileTable {
         self.table.is_empty()
     }
 
-    pub fn dup(&mut self, fd: FileDesc, new_fd: FileDesc, flags: FdFlags) -> Result<FileDesc> {
+    /// Duplicates the file at `fd` into the lowest available file descriptor that is equal to or
+    /// greater than `new_fd`.
+    ///
+    /// It fails with `EMFILE` if the new file descriptor is not less than `max_fds`.
+    pub fn dup(
+        &mut self,
+        fd: FileDesc,
+        new_fd: FileDesc,
+        flags: FdFlags,
+        max_fds: usize,
+    ) -> Result<FileDesc> {
         let file = self
             .table
             .get(fd as usize)
             .map(|entry| entry.file.clone())
             .ok_or(Error::with_message(Errno::ENOENT, "No such file"))?;
 
-        // Get the lowest-numbered available fd equal to or greater than `new_fd`.
-        let get_min_free_fd = || -> usize {
-            let new_fd = new_fd as usize;
-            if self.table.get(new_fd).is_none() {
-                return new_fd;
-            }
-
-            for idx in new_fd + 1..self.len() {
-                if self.table.get(idx).is_none() {
-                    return idx;
-                }
-            }
-            self.len()
-        };
-
-        let min_free_fd = get_min_free_fd();
+        let min_free_fd = self.get_min_free_fd(new_fd as usize);
+        if min_free_fd >= max_fds {
+            return_errno_with_message!(Errno::EMFILE, "too many open files");
+        }
         let entry = FileTableEntry::new(file, flags);
         self.table.put_at(min_free_fd, entry);
         Ok(min_free_fd as FileDesc)
     }
 
-    pub fn insert(&mut self, item: Arc<dyn FileLike>, flags: FdFlags) -> FileDesc {
+    /// Inserts the file into the lowest available file descriptor.
+    ///
+    /// It fails with `EMFILE` if the new file descriptor is not less than `max_fds`.
+    pub fn insert(
+        &mut self,
+        item: Arc<dyn FileLike>,
+        flags: FdFlags,
+        max_fds: usize,
+    ) -> Result<FileDesc> {
+        let min_free_fd = self.get_min_free_fd(0);
+        if min_free_fd >= max_fds {
+            return_errno_with_message!(Errno::EMFILE, "too many open files");
+        }
         let entry = FileTableEntry::new(item, flags);
-        self.table.put(entry) as FileDesc
+        self.table.put_at(min_free_fd, entry);
+        Ok(min_free_fd as FileDesc)
+    }
+
+    /// Gets the lowest-numbered available fd equal to or greater than `start`.
+    fn get_min_free_fd(&self, start: usize) -> usize {
+        (start..self.len())
+            .find(|idx| self.table.get(*idx).is_none())
+            .unwrap_or(self.len().max(start))
     }
 
     pub fn insert_at(
@@ -134,6 +153,15 @@ impl FileTable {
         if let Some(closed_inode_file) = closed_file.downcast_ref::<InodeHandle>() {
             // FIXME: Operation below should not hold any mutex if `self` is protected by a spinlock externally
             closed_inode_file.release_range_locks();
+            // Like Linux, the notification of a message queue is removed if the process
+            // closes any of its descriptors.
+            if let Some(queue) = closed_inode_file
+                .dentry()
+                .inode()
+                .downcast_ref::<MessageQueue>()
+            {
+                queue.flush();
+            }
         }
         Some(closed_file)
     }
@@ -172,6 +200,9 @@ impl FileTable {
             if let Some(inode_file) = removed_entry.file.downcast_ref::<InodeHandle>() {
                 // FIXME: Operation below should not hold any mutex if `self` is protected by a spinlock externally
                 inode_file.release_range_locks();
+                if let Some(queue) = inode_file.dentry().inode().downcast_ref::<MessageQueue>() {
+                    queue.flush();
+                }
             }
         }
 
@@ -257,6 +288,17 @@ impl WithFileTable for RwArc<FileTable> {
     }
 }
 
+/// Returns the maximum number of file descriptors that the process can use.
+///
+/// This is the soft limit of `RLIMIT_NOFILE`. It must be read before locking the file table, since
+/// the resource limits are protected by a sleeping lock.
+pub fn max_fds(process: &Process) -> usize {
+    let resource_limits = process.resource_limits().lock();
+    resource_limits
+        .get_rlimit(ResourceType::RLIMIT_NOFILE)
+        .get_cur() as usize
+}
+
 /// Gets a file from a file descriptor as fast as possible.
 ///
 /// `file_table` should be a mutable borrow of the file table contained in the `file_table` field
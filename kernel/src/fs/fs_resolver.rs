@@ -11,7 +11,7 @@ use super::{
     rootfs::root_mount,
     utils::{AccessMode, CreationFlags, InodeMode, InodeType, StatusFlags, PATH_MAX, SYMLINKS_MAX},
 };
-use crate::{prelude::*, process::posix_thread::AsThreadLocal};
+use crate::{prelude::*, process::posix_thread::AsThreadLocal, security};
 
 /// The file descriptor of the current working directory.
 pub const AT_FDCWD: FileDesc = -100;
@@ -112,7 +112,12 @@ impl FsResolver {
             );
         }
 
-        if creation_flags.contains(CreationFlags::O_TRUNC) {
+        let truncate = creation_flags.contains(CreationFlags::O_TRUNC);
+        if !open_args.status_flags.contains(StatusFlags::O_PATH) {
+            security::file_open(&target_dentry, open_args.access_mode, truncate)?;
+        }
+
+        if truncate {
             target_dentry.resize(0)?;
         }
         InodeHandle::new(target_dentry, open_args.access_mode, open_args.status_flags)
@@ -140,6 +145,7 @@ impl FsResolver {
         let tail_file_name = lookup_ctx.tail_file_name().unwrap();
         let new_dentry =
             parent.new_fs_child(&tail_file_name, InodeType::File, open_args.inode_mode)?;
+        security::file_open(&new_dentry, open_args.access_mode, false)?;
         // Don't check access mode for newly created file
         InodeHandle::new_unchecked_access(new_dentry, open_args.access_mode, open_args.status_flags)
     }
@@ -0,0 +1,197 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! The AES block cipher.
+//!
+//! See FIPS 197 for the specification. Like the generic implementation in Linux, the cipher is
+//! implemented with table lookups, so it is not hardened against cache-timing attacks.
+
+/// The size of a block in bytes.
+pub(super) const BLOCK_SIZE: usize = 16;
+
+/// The maximal number of rounds, which is used by AES-256.
+const MAX_ROUNDS: usize = 14;
+
+const SBOX: [u8; 256] = [
+    0x63, 0x7c, 0x77, 0x7b, 0xf2, 0x6b, 0x6f, 0xc5, 0x30, 0x01, 0x67, 0x2b, 0xfe, 0xd7, 0xab, 0x76,
+    0xca, 0x82, 0xc9, 0x7d, 0xfa, 0x59, 0x47, 0xf0, 0xad, 0xd4, 0xa2, 0xaf, 0x9c, 0xa4, 0x72, 0xc0,
+    0xb7, 0xfd, 0x93, 0x26, 0x36, 0x3f, 0xf7, 0xcc, 0x34, 0xa5, 0xe5, 0xf1, 0x71, 0xd8, 0x31, 0x15,
+    0x04, 0xc7, 0x23, 0xc3, 0x18, 0x96, 0x05, 0x9a, 0x07, 0x12, 0x80, 0xe2, 0xeb, 0x27, 0xb2, 0x75,
+    0x09, 0x83, 0x2c, 0x1a, 0x1b, 0x6e, 0x5a, 0xa0, 0x52, 0x3b, 0xd6, 0xb3, 0x29, 0xe3, 0x2f, 0x84,
+    0x53, 0xd1, 0x00, 0xed, 0x20, 0xfc, 0xb1, 0x5b, 0x6a, 0xcb, 0xbe, 0x39, 0x4a, 0x4c, 0x58, 0xcf,
+    0xd0, 0xef, 0xaa, 0xfb, 0x43, 0x4d, 0x33, 0x85, 0x45, 0xf9, 0x02, 0x7f, 0x50, 0x3c, 0x9f, 0xa8,
+    0x51, 0xa3, 0x40, 0x8f, 0x92, 0x9d, 0x38, 0xf5, 0xbc, 0xb6, 0xda, 0x21, 0x10, 0xff, 0xf3, 0xd2,
+    0xcd, 0x0c, 0x13, 0xec, 0x5f, 0x97, 0x44, 0x17, 0xc4, 0xa7, 0x7e, 0x3d, 0x64, 0x5d, 0x19, 0x73,
+    0x60, 0x81, 0x4f, 0xdc, 0x22, 0x2a, 0x90, 0x88, 0x46, 0xee, 0xb8, 0x14, 0xde, 0x5e, 0x0b, 0xdb,
+    0xe0, 0x32, 0x3a, 0x0a, 0x49, 0x06, 0x24, 0x5c, 0xc2, 0xd3, 0xac, 0x62, 0x91, 0x95, 0xe4, 0x79,
+    0xe7, 0xc8, 0x37, 0x6d, 0x8d, 0xd5, 0x4e, 0xa9, 0x6c, 0x56, 0xf4, 0xea, 0x65, 0x7a, 0xae, 0x08,
+    0xba, 0x78, 0x25, 0x2e, 0x1c, 0xa6, 0xb4, 0xc6, 0xe8, 0xdd, 0x74, 0x1f, 0x4b, 0xbd, 0x8b, 0x8a,
+    0x70, 0x3e, 0xb5, 0x66, 0x48, 0x03, 0xf6, 0x0e, 0x61, 0x35, 0x57, 0xb9, 0x86, 0xc1, 0x1d, 0x9e,
+    0xe1, 0xf8, 0x98, 0x11, 0x69, 0xd9, 0x8e, 0x94, 0x9b, 0x1e, 0x87, 0xe9, 0xce, 0x55, 0x28, 0xdf,
+    0x8c, 0xa1, 0x89, 0x0d, 0xbf, 0xe6, 0x42, 0x68, 0x41, 0x99, 0x2d, 0x0f, 0xb0, 0x54, 0xbb, 0x16,
+];
+
+const INV_SBOX: [u8; 256] = {
+    let mut inv_sbox = [0; 256];
+    let mut i = 0;
+    while i < 256 {
+        inv_sbox[SBOX[i] as usize] = i as u8;
+        i += 1;
+    }
+    inv_sbox
+};
+
+/// An AES cipher with an expanded key.
+pub(super) struct Aes {
+    round_keys: [[u8; BLOCK_SIZE]; MAX_ROUNDS + 1],
+    nrounds: usize,
+}
+
+impl Aes {
+    /// Creates a cipher with a 128-bit, 192-bit or 256-bit key.
+    pub(super) fn new(key: &[u8]) -> Self {
+        assert!(matches!(key.len(), 16 | 24 | 32));
+
+        let nkey_words = key.len() / 4;
+        let nrounds = nkey_words + 6;
+
+        let mut words = [[0u8; 4]; 4 * (MAX_ROUNDS + 1)];
+        for (word, chunk) in words.iter_mut().zip(key.chunks_exact(4)) {
+            word.copy_from_slice(chunk);
+        }
+        let mut rcon = 1;
+        for i in nkey_words..4 * (nrounds + 1) {
+            let mut temp = words[i - 1];
+            if i % nkey_words == 0 {
+                temp.rotate_left(1);
+                temp = temp.map(|byte| SBOX[byte as usize]);
+                temp[0] ^= rcon;
+                rcon = xtime(rcon);
+            } else if nkey_words > 6 && i % nkey_words == 4 {
+                temp = temp.map(|byte| SBOX[byte as usize]);
+            }
+            for j in 0..4 {
+                words[i][j] = words[i - nkey_words][j] ^ temp[j];
+            }
+        }
+
+        let mut round_keys = [[0; BLOCK_SIZE]; MAX_ROUNDS + 1];
+        for (round_key, round_words) in round_keys.iter_mut().zip(words.chunks_exact(4)) {
+            for (chunk, word) in round_key.chunks_exact_mut(4).zip(round_words) {
+                chunk.copy_from_slice(word);
+            }
+        }
+
+        Self {
+            round_keys,
+            nrounds,
+        }
+    }
+
+    /// Encrypts a block in place.
+    pub(super) fn encrypt_block(&self, block: &mut [u8; BLOCK_SIZE]) {
+        add_round_key(block, &self.round_keys[0]);
+        for round in 1..self.nrounds {
+            sub_bytes(block, &SBOX);
+            shift_rows(block);
+            mix_columns(block);
+            add_round_key(block, &self.round_keys[round]);
+        }
+        sub_bytes(block, &SBOX);
+        shift_rows(block);
+        add_round_key(block, &self.round_keys[self.nrounds]);
+    }
+
+    /// Decrypts a block in place.
+    pub(super) fn decrypt_block(&self, block: &mut [u8; BLOCK_SIZE]) {
+        add_round_key(block, &self.round_keys[self.nrounds]);
+        for round in (1..self.nrounds).rev() {
+            inv_shift_rows(block);
+            sub_bytes(block, &INV_SBOX);
+            add_round_key(block, &self.round_keys[round]);
+            inv_mix_columns(block);
+        }
+        inv_shift_rows(block);
+        sub_bytes(block, &INV_SBOX);
+        add_round_key(block, &self.round_keys[0]);
+    }
+}
+
+impl Drop for Aes {
+    fn drop(&mut self) {
+        // The round keys reveal the key, so they are wiped like other key materials.
+        self.round_keys = [[0; BLOCK_SIZE]; MAX_ROUNDS + 1];
+        core::hint::black_box(&self.round_keys);
+    }
+}
+
+fn add_round_key(block: &mut [u8; BLOCK_SIZE], round_key: &[u8; BLOCK_SIZE]) {
+    block
+        .iter_mut()
+        .zip(round_key)
+        .for_each(|(byte, key)| *byte ^= key);
+}
+
+fn sub_bytes(block: &mut [u8; BLOCK_SIZE], sbox: &[u8; 256]) {
+    block
+        .iter_mut()
+        .for_each(|byte| *byte = sbox[*byte as usize]);
+}
+
+// The block is stored in the column-major order, so the byte at row `r` and column `c` is
+// `block[r + 4 * c]`.
+
+fn shift_rows(block: &mut [u8; BLOCK_SIZE]) {
+    for row in 1..4 {
+        let bytes = [block[row], block[row + 4], block[row + 8], block[row + 12]];
+        for col in 0..4 {
+            block[row + 4 * col] = bytes[(col + row) % 4];
+        }
+    }
+}
+
+fn inv_shift_rows(block: &mut [u8; BLOCK_SIZE]) {
+    for row in 1..4 {
+        let bytes = [block[row], block[row + 4], block[row + 8], block[row + 12]];
+        for col in 0..4 {
+            block[row + 4 * col] = bytes[(col + 4 - row) % 4];
+        }
+    }
+}
+
+fn mix_columns(block: &mut [u8; BLOCK_SIZE]) {
+    for col in block.chunks_exact_mut(4) {
+        let [a0, a1, a2, a3] = [col[0], col[1], col[2], col[3]];
+        col[0] = xtime(a0) ^ xtime(a1) ^ a1 ^ a2 ^ a3;
+        col[1] = a0 ^ xtime(a1) ^ xtime(a2) ^ a2 ^ a3;
+        col[2] = a0 ^ a1 ^ xtime(a2) ^ xtime(a3) ^ a3;
+        col[3] = xtime(a0) ^ a0 ^ a1 ^ a2 ^ xtime(a3);
+    }
+}
+
+fn inv_mix_columns(block: &mut [u8; BLOCK_SIZE]) {
+    for col in block.chunks_exact_mut(4) {
+        let [a0, a1, a2, a3] = [col[0], col[1], col[2], col[3]];
+        col[0] = gmul(a0, 14) ^ gmul(a1, 11) ^ gmul(a2, 13) ^ gmul(a3, 9);
+        col[1] = gmul(a0, 9) ^ gmul(a1, 14) ^ gmul(a2, 11) ^ gmul(a3, 13);
+        col[2] = gmul(a0, 13) ^ gmul(a1, 9) ^ gmul(a2, 14) ^ gmul(a3, 11);
+        col[3] = gmul(a0, 11) ^ gmul(a1, 13) ^ gmul(a2, 9) ^ gmul(a3, 14);
+    }
+}
+
+/// Multiplies a byte by `x` in GF(2^8).
+const fn xtime(byte: u8) -> u8 {
+    (byte << 1) ^ if byte & 0x80 != 0 { 0x1b } else { 0 }
+}
+
+/// Multiplies two bytes in GF(2^8).
+fn gmul(mut a: u8, mut b: u8) -> u8 {
+    let mut product = 0;
+    while b != 0 {
+        if b & 1 != 0 {
+            product ^= a;
+        }
+        a = xtime(a);
+        b >>= 1;
+    }
+    product
+}
@@ -0,0 +1,62 @@
+// SPDX-License-Identifier: MPL-2.0
+
+use super::aes::BLOCK_SIZE;
+use crate::prelude::*;
+
+/// The maximal length of an encrypted name whose no-key name contains the whole name.
+///
+/// The no-key name of such a name has at most 252 bytes, so it fits in a file name.
+const MAX_UNDIGESTED_LEN: usize = 189;
+
+/// The length of the prefix of a long encrypted name that is kept in its no-key name.
+const DIGESTED_PREFIX_LEN: usize = 149;
+
+/// The length of the suffix of a long encrypted name that is kept in its no-key name.
+const DIGESTED_SUFFIX_LEN: usize = 2 * BLOCK_SIZE;
+
+/// The alphabet of the base64url encoding (see RFC 4648).
+const BASE64URL_CHARS: &[u8; 64] =
+    b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789-_";
+
+/// Returns the length of a name of `len` bytes after it is padded to be encrypted.
+///
+/// Like Linux, the name is padded with zeros to a multiple of `padding`, and to at least one
+/// block, but not beyond `max_len`.
+pub(super) fn padded_len(len: usize, padding: usize, max_len: usize) -> usize {
+    len.next_multiple_of(padding).max(BLOCK_SIZE).min(max_len)
+}
+
+/// Encodes an encrypted name into a no-key name, which is presented to the user space if the
+/// key is absent.
+///
+/// A short encrypted name is encoded with base64url. The long ones are encoded with an
+/// underscore followed by their prefixes and suffixes in base64url. With CBC, the last two
+/// blocks of the ciphertext depend on the whole name, so the no-key names are still unique.
+///
+/// Unlike Linux, the no-key names do not contain the hashes of the names, because Ext2 does
+/// not index the directories by the hashes.
+pub fn encode_nokey_name(encrypted_name: &[u8]) -> String {
+    if encrypted_name.len() <= MAX_UNDIGESTED_LEN {
+        return base64url_encode(encrypted_name);
+    }
+
+    let mut digested = Vec::with_capacity(DIGESTED_PREFIX_LEN + DIGESTED_SUFFIX_LEN);
+    digested.extend_from_slice(&encrypted_name[..DIGESTED_PREFIX_LEN]);
+    digested.extend_from_slice(&encrypted_name[encrypted_name.len() - DIGESTED_SUFFIX_LEN..]);
+    format!("_{}", base64url_encode(&digested))
+}
+
+fn base64url_encode(bytes: &[u8]) -> String {
+    let mut encoded = String::with_capacity(bytes.len().div_ceil(3) * 4);
+    for chunk in bytes.chunks(3) {
+        let bits = chunk.iter().enumerate().fold(0u32, |bits, (i, byte)| {
+            bits | (*byte as u32) << (16 - 8 * i)
+        });
+        // Like Linux, the encoded string is not padded with `=`.
+        for i in 0..=chunk.len() {
+            let index = (bits >> (18 - 6 * i)) & 0x3f;
+            encoded.push(BASE64URL_CHARS[index as usize] as char);
+        }
+    }
+    encoded
+}
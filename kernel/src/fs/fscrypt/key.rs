@@ -0,0 +1,199 @@
+// SPDX-License-Identifier: MPL-2.0
+
+use core::fmt::Write;
+
+use super::{
+    aes::{Aes, BLOCK_SIZE},
+    fname::padded_len,
+    modes::{cts_cbc_decrypt, cts_cbc_encrypt, xts_decrypt, xts_encrypt},
+    policy::{EncryptionContext, EncryptionMode},
+};
+use crate::{
+    fs::utils::InodeType,
+    prelude::*,
+    process::posix_thread::AsPosixThread,
+    security::keys::{search_thread_keyrings, KeyType},
+};
+
+/// The prefix of the descriptions of the master keys in the keyrings.
+const KEY_DESC_PREFIX: &str = "fscrypt:";
+
+/// The maximal size of a master key in bytes.
+const MAX_KEY_SIZE: usize = 64;
+
+/// The payload of a master key in the keyrings (`struct fscrypt_key`).
+#[repr(C)]
+#[derive(Clone, Copy, Pod)]
+struct MasterKeyPayload {
+    mode: u32,
+    raw: [u8; MAX_KEY_SIZE],
+    size: u32,
+}
+
+/// The encryption information of an inode, which has the key that encrypts its contents (for
+/// regular files) or its names (for directories and symbolic links).
+///
+/// The key is derived from the master key when the information is set up. Like the v1
+/// policies in Linux, the information remains valid until the inode is dropped, even if the
+/// master key is removed from the keyrings later.
+pub struct CryptInfo {
+    context: EncryptionContext,
+    cipher: InodeCipher,
+}
+
+enum InodeCipher {
+    /// The ciphers of AES-256-XTS, which encrypt the data and the tweaks, respectively.
+    Contents { data: Aes, tweak: Aes },
+    /// The cipher of AES-256-CTS.
+    Filenames(Aes),
+}
+
+impl CryptInfo {
+    /// Sets up the encryption information of an inode with its encryption context.
+    ///
+    /// The master key is searched in the keyrings of the current thread. This method fails
+    /// with `ENOKEY` if the master key is not found or is invalid.
+    pub fn new(context: EncryptionContext, inode_type: InodeType) -> Result<Self> {
+        let mode = match inode_type {
+            InodeType::File => context.contents_mode(),
+            InodeType::Dir | InodeType::SymLink => context.filenames_mode(),
+            _ => return_errno_with_message!(Errno::EINVAL, "the inode cannot be encrypted"),
+        };
+
+        let master_key = find_master_key(&context, mode.key_size())?;
+
+        // Like the v1 policies in Linux, the key is derived by encrypting the master key with
+        // AES-128-ECB, where the nonce is the key.
+        let mut derived_key = [0u8; MAX_KEY_SIZE];
+        let derived_key = &mut derived_key[..mode.key_size()];
+        derived_key.copy_from_slice(&master_key.raw[..mode.key_size()]);
+        let kdf_cipher = Aes::new(context.nonce());
+        for chunk in derived_key.chunks_exact_mut(BLOCK_SIZE) {
+            kdf_cipher.encrypt_block(chunk.try_into().unwrap());
+        }
+
+        let cipher = match mode {
+            EncryptionMode::Aes256Xts => InodeCipher::Contents {
+                data: Aes::new(&derived_key[..32]),
+                tweak: Aes::new(&derived_key[32..]),
+            },
+            EncryptionMode::Aes256Cts => InodeCipher::Filenames(Aes::new(derived_key)),
+        };
+        derived_key.fill(0);
+
+        Ok(Self { context, cipher })
+    }
+
+    /// Returns the encryption context.
+    pub fn context(&self) -> &EncryptionContext {
+        &self.context
+    }
+
+    /// Returns whether the information encrypts the contents of a regular file.
+    pub fn encrypts_contents(&self) -> bool {
+        matches!(self.cipher, InodeCipher::Contents { .. })
+    }
+
+    /// Encrypts a block of a regular file in place.
+    ///
+    /// `lblk` is the index of the block in the file, which is the IV of the block.
+    pub fn encrypt_block(&self, lblk: u64, block: &mut [u8]) {
+        let InodeCipher::Contents { data, tweak } = &self.cipher else {
+            panic!("the inode is not a regular file");
+        };
+        xts_encrypt(data, tweak, &block_iv(lblk), block);
+    }
+
+    /// Decrypts a block of a regular file in place.
+    pub fn decrypt_block(&self, lblk: u64, block: &mut [u8]) {
+        let InodeCipher::Contents { data, tweak } = &self.cipher else {
+            panic!("the inode is not a regular file");
+        };
+        xts_decrypt(data, tweak, &block_iv(lblk), block);
+    }
+
+    /// Encrypts a name in a directory, or the target of a symbolic link.
+    ///
+    /// The name is padded before it is encrypted. This method fails with `ENAMETOOLONG` if the
+    /// name is longer than `max_len`.
+    pub fn encrypt_name(&self, name: &[u8], max_len: usize) -> Result<Vec<u8>> {
+        let InodeCipher::Filenames(cipher) = &self.cipher else {
+            panic!("the inode is not a directory or a symbolic link");
+        };
+        if name.len() > max_len {
+            return_errno_with_message!(Errno::ENAMETOOLONG, "the name is too long");
+        }
+
+        let mut encrypted_name = name.to_vec();
+        encrypted_name.resize(
+            padded_len(name.len(), self.context.name_padding(), max_len),
+            0,
+        );
+        cts_cbc_encrypt(cipher, &mut encrypted_name);
+        Ok(encrypted_name)
+    }
+
+    /// Decrypts a name that is encrypted by [`Self::encrypt_name`].
+    ///
+    /// This method fails with `EUCLEAN` if the encrypted name is corrupted.
+    pub fn decrypt_name(&self, encrypted_name: &[u8]) -> Result<Vec<u8>> {
+        let InodeCipher::Filenames(cipher) = &self.cipher else {
+            panic!("the inode is not a directory or a symbolic link");
+        };
+        if encrypted_name.len() < BLOCK_SIZE {
+            return_errno_with_message!(Errno::EUCLEAN, "the encrypted name is too short");
+        }
+
+        let mut name = encrypted_name.to_vec();
+        cts_cbc_decrypt(cipher, &mut name);
+        // The padding zeros are removed.
+        let len = name
+            .iter()
+            .position(|byte| *byte == 0)
+            .unwrap_or(name.len());
+        name.truncate(len);
+        Ok(name)
+    }
+}
+
+impl Debug for CryptInfo {
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        f.debug_struct("CryptInfo")
+            .field("context", &self.context)
+            .finish_non_exhaustive()
+    }
+}
+
+/// Returns the IV of a block of a regular file, which is the little-endian block index.
+fn block_iv(lblk: u64) -> [u8; BLOCK_SIZE] {
+    let mut iv = [0; BLOCK_SIZE];
+    iv[..size_of::<u64>()].copy_from_slice(&lblk.to_le_bytes());
+    iv
+}
+
+/// Finds the master key of the context in the keyrings of the current thread.
+fn find_master_key(context: &EncryptionContext, min_key_size: usize) -> Result<MasterKeyPayload> {
+    let current = current_thread!();
+    let Some(posix_thread) = current.as_posix_thread() else {
+        return_errno_with_message!(Errno::ENOKEY, "kernel threads have no keyrings");
+    };
+
+    let mut description = String::from(KEY_DESC_PREFIX);
+    for byte in context.master_key_descriptor() {
+        write!(description, "{:02x}", byte).unwrap();
+    }
+
+    // Like Linux, an invalid key is treated as if it is absent.
+    let invalid_key = || Error::with_message(Errno::ENOKEY, "the master key is invalid");
+    let key = search_thread_keyrings(KeyType::Logon, &description, posix_thread)?;
+    let payload = key.payload().map_err(|_| invalid_key())?;
+    if payload.len() != size_of::<MasterKeyPayload>() {
+        return Err(invalid_key());
+    }
+    let master_key = MasterKeyPayload::from_bytes(&payload);
+    if (master_key.size as usize) < min_key_size || master_key.size as usize > MAX_KEY_SIZE {
+        return Err(invalid_key());
+    }
+
+    Ok(master_key)
+}
@@ -0,0 +1,24 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! Per-directory file encryption, which is compatible with the v1 encryption policies of
+//! `fscrypt` in Linux.
+//!
+//! An encryption policy is set on an empty directory. The contents of the regular files and
+//! the names in the directory tree are then encrypted transparently by the file system, with
+//! keys that are derived from a master key. The master key is a `logon` key in the keyrings,
+//! whose description is `fscrypt:` followed by the hexadecimal master key descriptor in the
+//! policy. Without the master key, the names are presented in an encoded form, and the
+//! contents of the regular files cannot be opened.
+//!
+//! Only AES-256-XTS for the contents and AES-256-CTS for the names are supported. The v2
+//! encryption policies, which manage the master keys with their own ioctls, are not supported.
+
+mod aes;
+mod fname;
+mod key;
+mod modes;
+mod policy;
+
+pub use fname::encode_nokey_name;
+pub use key::CryptInfo;
+pub use policy::{EncryptionContext, EncryptionPolicy};
@@ -0,0 +1,141 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! The block cipher modes of operation that encrypt the contents and the names.
+
+use super::aes::{Aes, BLOCK_SIZE};
+
+/// Encrypts a data unit in place with XTS (see IEEE 1619).
+///
+/// The length of the data unit must be a multiple of the block size.
+pub(super) fn xts_encrypt(
+    data_cipher: &Aes,
+    tweak_cipher: &Aes,
+    iv: &[u8; BLOCK_SIZE],
+    data: &mut [u8],
+) {
+    let mut tweak = *iv;
+    tweak_cipher.encrypt_block(&mut tweak);
+
+    for chunk in data.chunks_exact_mut(BLOCK_SIZE) {
+        let block: &mut [u8; BLOCK_SIZE] = chunk.try_into().unwrap();
+        xor_block(block, &tweak);
+        data_cipher.encrypt_block(block);
+        xor_block(block, &tweak);
+        mul_x(&mut tweak);
+    }
+}
+
+/// Decrypts a data unit in place with XTS (see IEEE 1619).
+///
+/// The length of the data unit must be a multiple of the block size.
+pub(super) fn xts_decrypt(
+    data_cipher: &Aes,
+    tweak_cipher: &Aes,
+    iv: &[u8; BLOCK_SIZE],
+    data: &mut [u8],
+) {
+    let mut tweak = *iv;
+    tweak_cipher.encrypt_block(&mut tweak);
+
+    for chunk in data.chunks_exact_mut(BLOCK_SIZE) {
+        let block: &mut [u8; BLOCK_SIZE] = chunk.try_into().unwrap();
+        xor_block(block, &tweak);
+        data_cipher.decrypt_block(block);
+        xor_block(block, &tweak);
+        mul_x(&mut tweak);
+    }
+}
+
+/// Encrypts a message in place with CBC and ciphertext stealing, whose IV is zero.
+///
+/// The message must be at least one block. Like `cts(cbc(aes))` in Linux, the CS3 variant is
+/// used, so the last two blocks of the ciphertext are always swapped (see NIST SP 800-38A
+/// Addendum).
+pub(super) fn cts_cbc_encrypt(cipher: &Aes, data: &mut [u8]) {
+    debug_assert!(data.len() >= BLOCK_SIZE);
+
+    let nblocks = data.len().div_ceil(BLOCK_SIZE);
+    let tail_len = data.len() - (nblocks - 1) * BLOCK_SIZE;
+
+    let mut prev = [0; BLOCK_SIZE];
+    for chunk in data[..(nblocks - 1) * BLOCK_SIZE].chunks_exact_mut(BLOCK_SIZE) {
+        let block: &mut [u8; BLOCK_SIZE] = chunk.try_into().unwrap();
+        xor_block(block, &prev);
+        cipher.encrypt_block(block);
+        prev = *block;
+    }
+    if nblocks == 1 {
+        let block: &mut [u8; BLOCK_SIZE] = data.try_into().unwrap();
+        xor_block(block, &prev);
+        cipher.encrypt_block(block);
+        return;
+    }
+
+    // The last partial block is padded with zeros and encrypted. Its ciphertext replaces the
+    // ciphertext of the second-to-last block, which is truncated and moved to the end.
+    let tail_start = (nblocks - 1) * BLOCK_SIZE;
+    let mut last = [0; BLOCK_SIZE];
+    last[..tail_len].copy_from_slice(&data[tail_start..]);
+    xor_block(&mut last, &prev);
+    cipher.encrypt_block(&mut last);
+
+    data[tail_start - BLOCK_SIZE..tail_start].copy_from_slice(&last);
+    data[tail_start..].copy_from_slice(&prev[..tail_len]);
+}
+
+/// Decrypts a message in place that is encrypted by [`cts_cbc_encrypt`].
+pub(super) fn cts_cbc_decrypt(cipher: &Aes, data: &mut [u8]) {
+    debug_assert!(data.len() >= BLOCK_SIZE);
+
+    let nblocks = data.len().div_ceil(BLOCK_SIZE);
+    let tail_len = data.len() - (nblocks - 1) * BLOCK_SIZE;
+
+    let mut prev = [0; BLOCK_SIZE];
+    if nblocks == 1 {
+        let block: &mut [u8; BLOCK_SIZE] = data.try_into().unwrap();
+        cipher.decrypt_block(block);
+        return;
+    }
+    for chunk in data[..(nblocks - 2) * BLOCK_SIZE].chunks_exact_mut(BLOCK_SIZE) {
+        let block: &mut [u8; BLOCK_SIZE] = chunk.try_into().unwrap();
+        let ciphertext = *block;
+        cipher.decrypt_block(block);
+        xor_block(block, &prev);
+        prev = ciphertext;
+    }
+
+    // Decrypting the second-to-last block of the ciphertext yields the last partial block of
+    // the plaintext, XORed with the ciphertext of the second-to-last block that is padded with
+    // the stolen bytes.
+    let tail_start = (nblocks - 1) * BLOCK_SIZE;
+    let mut last = [0; BLOCK_SIZE];
+    last.copy_from_slice(&data[tail_start - BLOCK_SIZE..tail_start]);
+    cipher.decrypt_block(&mut last);
+
+    let mut second_last = last;
+    second_last[..tail_len].copy_from_slice(&data[tail_start..]);
+    for (byte, cipher_byte) in last.iter_mut().zip(&second_last) {
+        *byte ^= cipher_byte;
+    }
+    cipher.decrypt_block(&mut second_last);
+    xor_block(&mut second_last, &prev);
+
+    data[tail_start - BLOCK_SIZE..tail_start].copy_from_slice(&second_last);
+    data[tail_start..].copy_from_slice(&last[..tail_len]);
+}
+
+fn xor_block(block: &mut [u8; BLOCK_SIZE], other: &[u8; BLOCK_SIZE]) {
+    block
+        .iter_mut()
+        .zip(other)
+        .for_each(|(byte, other)| *byte ^= other);
+}
+
+/// Multiplies the tweak by `x` in GF(2^128), where the tweak is in the little-endian order.
+fn mul_x(tweak: &mut [u8; BLOCK_SIZE]) {
+    let carry = tweak[BLOCK_SIZE - 1] >> 7;
+    for i in (1..BLOCK_SIZE).rev() {
+        tweak[i] = (tweak[i] << 1) | (tweak[i - 1] >> 7);
+    }
+    tweak[0] = (tweak[0] << 1) ^ (carry * 0x87);
+}
@@ -0,0 +1,166 @@
+// SPDX-License-Identifier: MPL-2.0
+
+use crate::{prelude::*, util::random::getrandom};
+
+/// The size of a master key descriptor in bytes.
+const KEY_DESCRIPTOR_SIZE: usize = 8;
+
+/// The size of the nonce in an encryption context in bytes.
+const NONCE_SIZE: usize = 16;
+
+/// The version of the v1 encryption policies.
+const POLICY_V1: u8 = 0;
+
+/// The version of the encryption contexts of the v1 encryption policies.
+const CONTEXT_V1: u8 = 1;
+
+/// The mask of the flags that select the padding of the encrypted names.
+const POLICY_FLAGS_PAD_MASK: u8 = 0x03;
+
+/// The encryption modes.
+#[repr(u8)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, TryFromInt)]
+pub(super) enum EncryptionMode {
+    /// AES-256-XTS, which encrypts the contents.
+    Aes256Xts = 1,
+    /// AES-256-CBC with ciphertext stealing, which encrypts the names.
+    Aes256Cts = 4,
+}
+
+impl EncryptionMode {
+    /// Returns the size of the key in bytes.
+    pub(super) fn key_size(self) -> usize {
+        match self {
+            Self::Aes256Xts => 64,
+            Self::Aes256Cts => 32,
+        }
+    }
+}
+
+/// An encryption policy (`struct fscrypt_policy_v1`).
+///
+/// The policy is set by the user space with `FS_IOC_SET_ENCRYPTION_POLICY` and retrieved with
+/// `FS_IOC_GET_ENCRYPTION_POLICY`.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Pod)]
+pub struct EncryptionPolicy {
+    version: u8,
+    contents_encryption_mode: u8,
+    filenames_encryption_mode: u8,
+    flags: u8,
+    master_key_descriptor: [u8; KEY_DESCRIPTOR_SIZE],
+}
+
+impl EncryptionPolicy {
+    /// Reads a policy from the user space.
+    ///
+    /// Like Linux, the version is read first to determine the size of the policy, and only the
+    /// v1 policies are accepted.
+    pub fn read_from_user(addr: Vaddr) -> Result<Self> {
+        let version = current_userspace!().read_val::<u8>(addr)?;
+        if version != POLICY_V1 {
+            return_errno_with_message!(Errno::EINVAL, "the policy version is not supported");
+        }
+        current_userspace!().read_val(addr)
+    }
+
+    /// Checks whether the policy is supported, failing with `EINVAL` if not.
+    fn check_supported(&self) -> Result<()> {
+        let contents_mode = EncryptionMode::try_from(self.contents_encryption_mode);
+        let filenames_mode = EncryptionMode::try_from(self.filenames_encryption_mode);
+        if !matches!(contents_mode, Ok(EncryptionMode::Aes256Xts))
+            || !matches!(filenames_mode, Ok(EncryptionMode::Aes256Cts))
+        {
+            return_errno_with_message!(Errno::EINVAL, "the encryption modes are not supported");
+        }
+        if self.flags & !POLICY_FLAGS_PAD_MASK != 0 {
+            return_errno_with_message!(Errno::EINVAL, "the policy flags are not supported");
+        }
+        Ok(())
+    }
+}
+
+/// The encryption context of an encrypted inode (`struct fscrypt_context_v1`).
+///
+/// The context is stored by the file system along with the inode. Besides the encryption
+/// policy, it has a random nonce, from which the key of the inode is derived.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Pod)]
+pub struct EncryptionContext {
+    version: u8,
+    contents_encryption_mode: u8,
+    filenames_encryption_mode: u8,
+    flags: u8,
+    master_key_descriptor: [u8; KEY_DESCRIPTOR_SIZE],
+    nonce: [u8; NONCE_SIZE],
+}
+
+impl EncryptionContext {
+    /// Creates the context of a directory whose encryption policy is being set.
+    pub fn new(policy: &EncryptionPolicy) -> Result<Self> {
+        policy.check_supported()?;
+
+        let mut context = Self {
+            version: CONTEXT_V1,
+            contents_encryption_mode: policy.contents_encryption_mode,
+            filenames_encryption_mode: policy.filenames_encryption_mode,
+            flags: policy.flags,
+            master_key_descriptor: policy.master_key_descriptor,
+            nonce: [0; NONCE_SIZE],
+        };
+        getrandom(&mut context.nonce);
+        Ok(context)
+    }
+
+    /// Creates the context of a new inode in the directory, which inherits the policy.
+    pub fn new_child(&self) -> Self {
+        let mut context = *self;
+        getrandom(&mut context.nonce);
+        context
+    }
+
+    /// Parses a context that is stored by the file system.
+    pub fn parse(bytes: &[u8]) -> Result<Self> {
+        if bytes.len() != size_of::<Self>() {
+            return_errno_with_message!(Errno::EINVAL, "the encryption context is invalid");
+        }
+        let context = Self::from_bytes(bytes);
+        if context.version != CONTEXT_V1 {
+            return_errno_with_message!(Errno::EINVAL, "the context version is not supported");
+        }
+        context.policy().check_supported()?;
+        Ok(context)
+    }
+
+    /// Returns the encryption policy.
+    pub fn policy(&self) -> EncryptionPolicy {
+        EncryptionPolicy {
+            version: POLICY_V1,
+            contents_encryption_mode: self.contents_encryption_mode,
+            filenames_encryption_mode: self.filenames_encryption_mode,
+            flags: self.flags,
+            master_key_descriptor: self.master_key_descriptor,
+        }
+    }
+
+    pub(super) fn contents_mode(&self) -> EncryptionMode {
+        EncryptionMode::try_from(self.contents_encryption_mode).unwrap()
+    }
+
+    pub(super) fn filenames_mode(&self) -> EncryptionMode {
+        EncryptionMode::try_from(self.filenames_encryption_mode).unwrap()
+    }
+
+    pub(super) fn master_key_descriptor(&self) -> &[u8; KEY_DESCRIPTOR_SIZE] {
+        &self.master_key_descriptor
+    }
+
+    pub(super) fn nonce(&self) -> &[u8; NONCE_SIZE] {
+        &self.nonce
+    }
+
+    /// Returns the length to which the encrypted names are padded.
+    pub(super) fn name_padding(&self) -> usize {
+        4 << (self.flags & POLICY_FLAGS_PAD_MASK)
+    }
+}
@@ -22,6 +22,9 @@ impl InodeHandle<Rights> {
         if inode.type_() == InodeType::Dir && access_mode.is_writable() {
             return_errno_with_message!(Errno::EISDIR, "directory cannot open to write");
         }
+        if !status_flags.contains(StatusFlags::O_PATH) {
+            inode.prepare_open()?;
+        }
 
         let file_io = if let Some(device) = inode.as_device() {
             device.open()?
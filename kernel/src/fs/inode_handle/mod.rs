@@ -25,6 +25,7 @@ use crate::{
     },
     prelude::*,
     process::{
+        rlimit::check_file_write,
         signal::{PollHandle, Pollable},
         Gid, Uid,
     },
@@ -106,6 +107,15 @@ impl InodeHandle_ {
             offset = self.dentry.size();
         }
 
+        if self.dentry.type_() == InodeType::File {
+            let max_len = check_file_write(offset, reader.remain())?;
+            if max_len < reader.remain() {
+                // `VmReader::limit` takes the reader by value, so the reader is replaced.
+                let empty_reader = VmReader::from(&[] as &[u8]).to_fallible();
+                *reader = core::mem::replace(reader, empty_reader).limit(max_len);
+            }
+        }
+
         if status_flags.contains(StatusFlags::O_DIRECT) {
             self.dentry.inode().write_direct_at(offset, reader)
         } else {
@@ -0,0 +1,315 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! The io_uring asynchronous I/O interface.
+//!
+//! An io_uring instance consists of a submission queue (SQ) and a completion queue (CQ) that are
+//! shared with the user space. The user space submits I/O requests by filling in the SQ entries
+//! (SQEs) and calling `io_uring_enter`, then reaps the results from the CQ entries (CQEs).
+//!
+//! The requests are executed by kernel worker threads, so they never block the submitter. Since
+//! the worker threads cannot access the user space, the data to be written is copied when the
+//! requests are submitted, and the outputs that must be written to the user space (e.g., the
+//! data read from files and the accepted sockets) are delivered when the user space calls
+//! `io_uring_enter` next time. Until then, the file is reported to be readable so that the user
+//! space can wait for the completions with `poll` or `epoll`. Such outputs can only be delivered
+//! to the address space of the submitter, so they are canceled if another process (e.g., a child
+//! that inherits the file) calls `io_uring_enter` first.
+//!
+//! Reference: <https://man7.org/linux/man-pages/man7/io_uring.7.html>.
+
+use core::sync::atomic::{AtomicU64, Ordering};
+
+use aster_rights::Rights;
+use ostd::sync::WaitQueue;
+
+use self::{
+    op::{Op, Output},
+    ring::Rings,
+    uapi::{IoUringCqe, IoUringParams, IORING_OFF_CQ_RING, IORING_OFF_SQES, IORING_OFF_SQ_RING},
+    worker::IoWorkers,
+};
+use crate::{
+    events::IoEvents,
+    fs::{
+        file_handle::FileLike,
+        utils::{InodeMode, Metadata},
+    },
+    prelude::*,
+    process::{
+        signal::{Pause, PollHandle, Pollable, Pollee},
+        ProcessVm,
+    },
+    vm::vmo::Vmo,
+};
+
+mod op;
+mod ring;
+pub mod uapi;
+mod worker;
+
+/// An io_uring instance.
+pub struct IoUring {
+    inner: Arc<RingInner>,
+}
+
+struct RingInner {
+    rings: Rings,
+    /// The lock that serializes the consumers of the SQ ring.
+    submission_lock: Mutex<()>,
+    /// The completions that have not been posted to the CQ ring.
+    ///
+    /// The lock also serializes the producers of the CQ ring.
+    deferred: Mutex<VecDeque<Completion>>,
+    /// The wait queue that is woken up whenever a request completes.
+    completion_wq: WaitQueue,
+    /// The number of completed requests, excluding the timeouts.
+    num_completed: AtomicU64,
+    pollee: Pollee,
+    workers: Arc<IoWorkers>,
+}
+
+struct Completion {
+    user_data: u64,
+    result: Result<Output>,
+    /// The virtual memory of the submitter, where the output should be delivered.
+    submitter: Weak<ProcessVm>,
+}
+
+impl IoUring {
+    /// Creates an io_uring instance.
+    ///
+    /// Both `sq_entries` and `cq_entries` must be powers of two.
+    pub fn new(sq_entries: u32, cq_entries: u32) -> Result<Arc<Self>> {
+        let inner = RingInner {
+            rings: Rings::new(sq_entries, cq_entries)?,
+            submission_lock: Mutex::new(()),
+            deferred: Mutex::new(VecDeque::new()),
+            completion_wq: WaitQueue::new(),
+            num_completed: AtomicU64::new(0),
+            pollee: Pollee::new(),
+            workers: IoWorkers::new(),
+        };
+
+        Ok(Arc::new(Self {
+            inner: Arc::new(inner),
+        }))
+    }
+
+    /// Fills in the ring offsets of the parameters of `io_uring_setup`.
+    pub fn fill_offsets(&self, params: &mut IoUringParams) {
+        params.sq_off = self.inner.rings.sq_offsets();
+        params.cq_off = self.inner.rings.cq_offsets();
+    }
+
+    /// Submits at most `to_submit` requests from the SQ ring.
+    ///
+    /// Returns the number of consumed SQEs. Requests that fail to be prepared complete with an
+    /// error in their CQEs, which does not stop the submission.
+    pub fn submit(&self, to_submit: u32, ctx: &Context) -> Result<u32> {
+        self.inner.submit(to_submit, ctx)
+    }
+
+    /// Posts the deferred completions, and waits until there are at least `min_complete` CQEs
+    /// in the CQ ring.
+    pub fn wait_completions(&self, min_complete: u32, ctx: &Context) -> Result<()> {
+        self.inner.flush(ctx);
+
+        let min_complete = min_complete.min(self.inner.rings.cq_entries());
+        if min_complete == 0 {
+            return Ok(());
+        }
+
+        self.inner.completion_wq.pause_until(|| {
+            self.inner.flush(ctx);
+            let num_ready = self.inner.rings.num_ready_cqes().unwrap_or(u32::MAX);
+            (num_ready >= min_complete).then_some(())
+        })
+    }
+}
+
+impl RingInner {
+    fn submit(self: &Arc<Self>, to_submit: u32, ctx: &Context) -> Result<u32> {
+        let _guard = self.submission_lock.lock();
+        let submitter = Arc::downgrade(&ctx.thread_local.process_vm());
+
+        let mut num_submitted = 0;
+        while num_submitted < to_submit {
+            let Some(sqe) = self.rings.pop_sqe()? else {
+                break;
+            };
+            num_submitted += 1;
+
+            let user_data = sqe.user_data;
+            let op = match Op::prepare(&sqe, self, ctx) {
+                Ok(op) => op,
+                Err(err) => {
+                    self.complete(user_data, Err(err), true, submitter.clone());
+                    continue;
+                }
+            };
+
+            let is_counted = op.is_counted();
+            if op.is_inline() {
+                self.complete(user_data, op.execute(self), is_counted, submitter.clone());
+                continue;
+            }
+
+            let ring = self.clone();
+            let submitter = submitter.clone();
+            self.workers.submit(Box::new(move || {
+                let result = match op.execute(&ring) {
+                    // The waiting operation is interrupted because the pool is shut down.
+                    Err(_) if ring.workers.is_shutdown() => Err(Error::with_message(
+                        Errno::ECANCELED,
+                        "the io_uring instance is closed",
+                    )),
+                    result => result,
+                };
+                ring.complete(user_data, result, is_counted, submitter);
+            }));
+        }
+
+        Ok(num_submitted)
+    }
+
+    /// Completes a request.
+    ///
+    /// The CQE is posted immediately if possible. Otherwise, the completion is deferred until
+    /// the next [`Self::flush`].
+    fn complete(
+        &self,
+        user_data: u64,
+        result: Result<Output>,
+        is_counted: bool,
+        submitter: Weak<ProcessVm>,
+    ) {
+        let completion = Completion {
+            user_data,
+            result,
+            submitter,
+        };
+        {
+            let mut deferred = self.deferred.lock();
+            let is_posted = completion
+                .cqe_without_context()
+                .is_some_and(|cqe| self.post_cqe(&cqe));
+            if !is_posted {
+                deferred.push_back(completion);
+            }
+        }
+
+        if is_counted {
+            self.num_completed.fetch_add(1, Ordering::Relaxed);
+        }
+        self.completion_wq.wake_all();
+        self.pollee.notify(IoEvents::IN);
+    }
+
+    /// Posts the deferred completions to the CQ ring until the CQ ring is full.
+    fn flush(&self, ctx: &Context) {
+        let mut deferred = self.deferred.lock();
+
+        while !deferred.is_empty() && !self.rings.is_cq_full().unwrap_or(true) {
+            let completion = deferred.pop_front().unwrap();
+            let res = completion
+                .result
+                .and_then(|output| output.deliver(&completion.submitter, ctx));
+            self.post_cqe(&new_cqe(completion.user_data, res));
+        }
+    }
+
+    /// Posts a CQE to the CQ ring, returning `false` if the CQ ring is full.
+    ///
+    /// This method must be called with the lock of the deferred completions held.
+    fn post_cqe(&self, cqe: &IoUringCqe) -> bool {
+        match self.rings.post_cqe(cqe) {
+            Ok(is_posted) => is_posted,
+            Err(err) => {
+                // Since the ring VMO is allocated by the kernel, this should never happen.
+                warn!("failed to post a CQE: {:?}", err);
+                true
+            }
+        }
+    }
+
+    fn num_completed(&self) -> u64 {
+        self.num_completed.load(Ordering::Relaxed)
+    }
+
+    fn completion_wq(&self) -> &WaitQueue {
+        &self.completion_wq
+    }
+
+    fn check_io_events(&self) -> IoEvents {
+        let mut events = IoEvents::OUT;
+
+        let has_cqes = self.rings.num_ready_cqes().is_ok_and(|num| num > 0);
+        if has_cqes || !self.deferred.lock().is_empty() {
+            events |= IoEvents::IN;
+        }
+
+        events
+    }
+}
+
+impl Completion {
+    /// Returns the CQE if it can be posted without the context of a user thread.
+    fn cqe_without_context(&self) -> Option<IoUringCqe> {
+        match &self.result {
+            Ok(Output::Done(len)) => Some(new_cqe(self.user_data, Ok(*len))),
+            Ok(_) => None,
+            Err(err) => Some(new_cqe(self.user_data, Err(*err))),
+        }
+    }
+}
+
+fn new_cqe(user_data: u64, res: Result<usize>) -> IoUringCqe {
+    let res = match res {
+        Ok(len) => len as i32,
+        Err(err) => -(err.error() as i32),
+    };
+    IoUringCqe {
+        user_data,
+        res,
+        flags: 0,
+    }
+}
+
+impl Drop for IoUring {
+    fn drop(&mut self) {
+        self.inner.workers.shutdown();
+    }
+}
+
+impl Pollable for IoUring {
+    fn poll(&self, mask: IoEvents, poller: Option<&mut PollHandle>) -> IoEvents {
+        // The user space consumes the CQEs without notifying the kernel, so the cached events
+        // may be outdated.
+        self.inner.pollee.invalidate();
+        self.inner
+            .pollee
+            .poll_with(mask, poller, || self.inner.check_io_events())
+    }
+}
+
+impl FileLike for IoUring {
+    fn mmap_vmo(&self, offset: usize) -> Result<(Vmo<Rights>, usize)> {
+        let rings = &self.inner.rings;
+        let vmo = match offset {
+            IORING_OFF_SQ_RING | IORING_OFF_CQ_RING => rings.ring_vmo().dup()?,
+            IORING_OFF_SQES => rings.sqes_vmo().dup()?,
+            _ => return_errno_with_message!(Errno::EINVAL, "the offset is not a ring offset"),
+        };
+        Ok((vmo, 0))
+    }
+
+    fn metadata(&self) -> Metadata {
+        // This is a dummy implementation.
+        // TODO: Add "anonymous inode fs" and link `IoUring` to it.
+        Metadata::new_file(
+            0,
+            InodeMode::from_bits_truncate(0o600),
+            aster_block::BLOCK_SIZE,
+        )
+    }
+}
@@ -0,0 +1,380 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! The I/O operations of io_uring.
+//!
+//! An operation goes through three stages:
+//! 1. It is prepared from an SQE in the context of the submitter, where the file descriptors are
+//!    resolved and the data to be written is copied from the user space.
+//! 2. It is executed by a worker thread, which may block.
+//! 3. Its output is delivered. Some outputs (e.g., the received data and the accepted sockets)
+//!    can only be delivered in the context of a user thread, since the worker threads have no
+//!    access to the user space.
+
+use core::time::Duration;
+
+use super::{
+    uapi::{IoSqeFlags, IoTimeoutFlags, IoUringOp, IoUringSqe},
+    RingInner,
+};
+use crate::{
+    events::IoEvents,
+    fs::{
+        file_handle::FileLike,
+        file_table::{get_file_fast, max_fds, FdFlags, FileDesc},
+        utils::StatusFlags,
+    },
+    net::socket::{MessageHeader, SendRecvFlags, SocketAddr},
+    prelude::*,
+    process::{
+        signal::{Pause, Poller},
+        ProcessVm,
+    },
+    time::{clocks::MonotonicClock, timespec_t},
+    util::net::{write_socket_addr_to_user, SockFlags},
+};
+
+/// The maximum number of bytes that an operation can transfer.
+///
+/// The data is staged in a kernel buffer, so a request with a larger length completes with a
+/// short count.
+const MAX_IO_LEN: usize = 1024 * 1024;
+
+/// A prepared I/O operation.
+pub(super) enum Op {
+    Nop,
+    Read {
+        file: Arc<dyn FileLike>,
+        /// The offset to read from, or `None` to use and update the file offset.
+        offset: Option<usize>,
+        addr: Vaddr,
+        len: usize,
+    },
+    Write {
+        file: Arc<dyn FileLike>,
+        /// The offset to write to, or `None` to use and update the file offset.
+        offset: Option<usize>,
+        data: Vec<u8>,
+    },
+    Send {
+        file: Arc<dyn FileLike>,
+        data: Vec<u8>,
+        flags: SendRecvFlags,
+    },
+    Recv {
+        file: Arc<dyn FileLike>,
+        addr: Vaddr,
+        len: usize,
+        flags: SendRecvFlags,
+    },
+    Accept {
+        file: Arc<dyn FileLike>,
+        addr_ptr: Vaddr,
+        addrlen_ptr: Vaddr,
+        flags: SockFlags,
+    },
+    Timeout {
+        duration: Duration,
+        /// The number of completions to wait for, or `None` to wait for the timeout only.
+        target: Option<u64>,
+    },
+}
+
+impl Op {
+    /// Prepares an operation from the SQE.
+    pub(super) fn prepare(sqe: &IoUringSqe, ring: &RingInner, ctx: &Context) -> Result<Self> {
+        let Some(sqe_flags) = IoSqeFlags::from_bits(sqe.flags) else {
+            return_errno_with_message!(Errno::EINVAL, "the SQE flags are invalid");
+        };
+        if !sqe_flags.is_empty() {
+            return_errno_with_message!(Errno::EINVAL, "the SQE flags are not supported");
+        }
+        if sqe.ioprio != 0 || sqe.buf_index != 0 || sqe.personality != 0 {
+            return_errno_with_message!(Errno::EINVAL, "the SQE fields are not supported");
+        }
+
+        let opcode = IoUringOp::try_from(sqe.opcode)
+            .map_err(|_| Error::with_message(Errno::EINVAL, "the opcode is invalid"))?;
+        let len = (sqe.len as usize).min(MAX_IO_LEN);
+
+        let op = match opcode {
+            IoUringOp::IORING_OP_NOP => Self::Nop,
+            IoUringOp::IORING_OP_READ => Self::Read {
+                file: get_file(sqe.fd, ctx)?,
+                offset: parse_offset(sqe.off)?,
+                addr: sqe.addr as Vaddr,
+                len,
+            },
+            IoUringOp::IORING_OP_WRITE => Self::Write {
+                file: get_file(sqe.fd, ctx)?,
+                offset: parse_offset(sqe.off)?,
+                data: read_data(sqe.addr as Vaddr, len, ctx)?,
+            },
+            IoUringOp::IORING_OP_SEND => Self::Send {
+                file: get_socket(sqe.fd, ctx)?,
+                data: read_data(sqe.addr as Vaddr, len, ctx)?,
+                flags: SendRecvFlags::from_bits_truncate(sqe.op_flags as i32),
+            },
+            IoUringOp::IORING_OP_RECV => Self::Recv {
+                file: get_socket(sqe.fd, ctx)?,
+                addr: sqe.addr as Vaddr,
+                len,
+                flags: SendRecvFlags::from_bits_truncate(sqe.op_flags as i32),
+            },
+            IoUringOp::IORING_OP_ACCEPT => Self::Accept {
+                file: get_socket(sqe.fd, ctx)?,
+                addr_ptr: sqe.addr as Vaddr,
+                addrlen_ptr: sqe.off as Vaddr,
+                flags: SockFlags::from_bits(sqe.op_flags as i32)
+                    .ok_or_else(|| Error::with_message(Errno::EINVAL, "invalid accept flags"))?,
+            },
+            IoUringOp::IORING_OP_TIMEOUT => {
+                if sqe.len != 1 {
+                    return_errno_with_message!(Errno::EINVAL, "the timespec count is not one");
+                }
+                let Some(timeout_flags) = IoTimeoutFlags::from_bits(sqe.op_flags) else {
+                    return_errno_with_message!(Errno::EINVAL, "the timeout flags are invalid");
+                };
+
+                let timespec = ctx.user_space().read_val::<timespec_t>(sqe.addr as Vaddr)?;
+                let mut duration = Duration::try_from(timespec)?;
+                if timeout_flags.contains(IoTimeoutFlags::IORING_TIMEOUT_ABS) {
+                    let now = MonotonicClock::get().read_time();
+                    duration = duration.saturating_sub(now);
+                }
+
+                let target = (sqe.off != 0).then(|| ring.num_completed() + sqe.off);
+                Self::Timeout { duration, target }
+            }
+            _ => return_errno_with_message!(Errno::EINVAL, "the opcode is not supported"),
+        };
+
+        Ok(op)
+    }
+
+    /// Returns whether the operation can be completed without a worker thread.
+    pub(super) fn is_inline(&self) -> bool {
+        matches!(self, Self::Nop)
+    }
+
+    /// Returns whether the completion of the operation counts towards the timeouts.
+    pub(super) fn is_counted(&self) -> bool {
+        !matches!(self, Self::Timeout { .. })
+    }
+
+    /// Executes the operation.
+    ///
+    /// This method may block until the operation is done.
+    pub(super) fn execute(self, ring: &RingInner) -> Result<Output> {
+        match self {
+            Self::Nop => Ok(Output::Done(0)),
+            Self::Read {
+                file,
+                offset,
+                addr,
+                len,
+            } => {
+                let mut buf = vec![0u8; len];
+                let read_len = retry_on_eagain(file.as_ref(), IoEvents::IN, || {
+                    let mut writer = VmWriter::from(buf.as_mut_slice()).to_fallible();
+                    match offset {
+                        Some(offset) => file.read_at(offset, &mut writer),
+                        None => file.read(&mut writer),
+                    }
+                })?;
+                buf.truncate(read_len);
+                Ok(Output::CopyOut { addr, buf })
+            }
+            Self::Write { file, offset, data } => {
+                let written_len = retry_on_eagain(file.as_ref(), IoEvents::OUT, || {
+                    let mut reader = VmReader::from(data.as_slice()).to_fallible();
+                    match offset {
+                        Some(offset) => file.write_at(offset, &mut reader),
+                        None => file.write(&mut reader),
+                    }
+                })?;
+                Ok(Output::Done(written_len))
+            }
+            Self::Send { file, data, flags } => {
+                let socket = file.as_socket_or_err()?;
+                let sent_len = retry_on_eagain(file.as_ref(), IoEvents::OUT, || {
+                    let mut reader = VmReader::from(data.as_slice()).to_fallible();
+                    socket.sendmsg(&mut reader, MessageHeader::new(None, Vec::new()), flags)
+                })?;
+                Ok(Output::Done(sent_len))
+            }
+            Self::Recv {
+                file,
+                addr,
+                len,
+                flags,
+            } => {
+                let socket = file.as_socket_or_err()?;
+                let mut buf = vec![0u8; len];
+                let (received_len, _) = retry_on_eagain(file.as_ref(), IoEvents::IN, || {
+                    let mut writer = VmWriter::from(buf.as_mut_slice()).to_fallible();
+                    socket.recvmsg(&mut writer, flags)
+                })?;
+                buf.truncate(received_len);
+                Ok(Output::CopyOut { addr, buf })
+            }
+            Self::Accept {
+                file,
+                addr_ptr,
+                addrlen_ptr,
+                flags,
+            } => {
+                let socket = file.as_socket_or_err()?;
+                let (file, socket_addr) =
+                    retry_on_eagain(file.as_ref(), IoEvents::IN, || socket.accept())?;
+                Ok(Output::Accept {
+                    file,
+                    socket_addr,
+                    addr_ptr,
+                    addrlen_ptr,
+                    flags,
+                })
+            }
+            Self::Timeout { duration, target } => {
+                ring.completion_wq().pause_until_or_timeout(
+                    || {
+                        let target = target?;
+                        (ring.num_completed() >= target).then_some(())
+                    },
+                    &duration,
+                )?;
+                Ok(Output::Done(0))
+            }
+        }
+    }
+}
+
+/// The output of an executed operation.
+pub(super) enum Output {
+    /// The operation is done with the result.
+    Done(usize),
+    /// The data should be copied to the user space.
+    CopyOut { addr: Vaddr, buf: Vec<u8> },
+    /// The accepted socket should be installed in the file table.
+    Accept {
+        file: Arc<dyn FileLike>,
+        socket_addr: SocketAddr,
+        addr_ptr: Vaddr,
+        addrlen_ptr: Vaddr,
+        flags: SockFlags,
+    },
+}
+
+impl Output {
+    /// Delivers the output, returning the result of the operation.
+    ///
+    /// This method must be called in the context of a user thread, unless the output is
+    /// [`Output::Done`]. The user thread must run in the virtual memory of the submitter,
+    /// otherwise the output is discarded and the operation fails with `ECANCELED`.
+    pub(super) fn deliver(self, submitter: &Weak<ProcessVm>, ctx: &Context) -> Result<usize> {
+        if let Self::Done(res) = self {
+            return Ok(res);
+        }
+
+        // The user-space addresses and the file table belong to the submitter.
+        if !core::ptr::eq(
+            submitter.as_ptr(),
+            Arc::as_ptr(&ctx.thread_local.process_vm()),
+        ) {
+            return_errno_with_message!(
+                Errno::ECANCELED,
+                "the output cannot be delivered to another address space"
+            );
+        }
+
+        match self {
+            Self::Done(res) => Ok(res),
+            Self::CopyOut { addr, buf } => {
+                ctx.user_space()
+                    .write_bytes(addr, &mut VmReader::from(buf.as_slice()))?;
+                Ok(buf.len())
+            }
+            Self::Accept {
+                file,
+                socket_addr,
+                addr_ptr,
+                addrlen_ptr,
+                flags,
+            } => {
+                if flags.contains(SockFlags::SOCK_NONBLOCK) {
+                    file.set_status_flags(StatusFlags::O_NONBLOCK)?;
+                }
+                if addr_ptr != 0 {
+                    write_socket_addr_to_user(&socket_addr, addr_ptr, addrlen_ptr)?;
+                }
+
+                let fd_flags = if flags.contains(SockFlags::SOCK_CLOEXEC) {
+                    FdFlags::CLOEXEC
+                } else {
+                    FdFlags::empty()
+                };
+                let max_fds = max_fds(&ctx.process);
+                let file_table = ctx.thread_local.file_table().borrow();
+                let fd = file_table.write().insert(file, fd_flags, max_fds)?;
+                Ok(fd as usize)
+            }
+        }
+    }
+}
+
+fn get_file(fd: FileDesc, ctx: &Context) -> Result<Arc<dyn FileLike>> {
+    let mut file_table = ctx.thread_local.file_table().borrow_mut();
+    let file = get_file_fast!(&mut file_table, fd).into_owned();
+    Ok(file)
+}
+
+fn get_socket(fd: FileDesc, ctx: &Context) -> Result<Arc<dyn FileLike>> {
+    let file = get_file(fd, ctx)?;
+    file.as_socket_or_err()?;
+    Ok(file)
+}
+
+/// Parses the offset of a read or write operation.
+///
+/// The offset of `-1` means using and updating the file offset.
+fn parse_offset(offset: u64) -> Result<Option<usize>> {
+    if offset == u64::MAX {
+        return Ok(None);
+    }
+    if offset > isize::MAX as u64 {
+        return_errno_with_message!(Errno::EINVAL, "the offset is too large");
+    }
+    Ok(Some(offset as usize))
+}
+
+fn read_data(addr: Vaddr, len: usize, ctx: &Context) -> Result<Vec<u8>> {
+    let mut data = vec![0u8; len];
+    ctx.user_space()
+        .read_bytes(addr, &mut VmWriter::from(data.as_mut_slice()))?;
+    Ok(data)
+}
+
+/// Runs the operation, and retries it when the events occur if the file is not ready.
+///
+/// Unlike the blocking I/O of the file, this works regardless of the `O_NONBLOCK` flag, which
+/// matches the behavior of io_uring.
+fn retry_on_eagain<F, R>(file: &dyn FileLike, mask: IoEvents, mut op: F) -> Result<R>
+where
+    F: FnMut() -> Result<R>,
+{
+    match op() {
+        Err(err) if err.error() == Errno::EAGAIN => (),
+        res => return res,
+    }
+
+    let mut poller = Poller::new();
+    loop {
+        let revents = file.poll(mask | IoEvents::ALWAYS_POLL, Some(poller.as_handle_mut()));
+        if !revents.is_empty() {
+            match op() {
+                Err(err) if err.error() == Errno::EAGAIN => (),
+                res => return res,
+            }
+        }
+        poller.wait(None)?;
+    }
+}
@@ -0,0 +1,188 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! The rings shared with the user space.
+//!
+//! The submission queue (SQ) ring, the completion queue (CQ) ring and the CQE array share one
+//! VMO, while the SQE array resides in another VMO. Both VMOs are mapped by the user space via
+//! `mmap`. The layout of the shared VMO is described by [`IoSqringOffsets`] and
+//! [`IoCqringOffsets`], which are reported to the user space in `io_uring_setup`.
+
+use core::{
+    mem::size_of,
+    sync::atomic::{fence, Ordering},
+};
+
+use align_ext::AlignExt;
+use aster_rights::Rights;
+use ostd::mm::VmIo;
+
+use super::uapi::{IoCqringOffsets, IoSqringOffsets, IoUringCqe, IoUringSqe};
+use crate::{
+    prelude::*,
+    vm::vmo::{Vmo, VmoOptions},
+};
+
+const SQ_HEAD: usize = 0;
+const SQ_TAIL: usize = 4;
+const SQ_RING_MASK: usize = 8;
+const SQ_RING_ENTRIES: usize = 12;
+const SQ_FLAGS: usize = 16;
+const SQ_DROPPED: usize = 20;
+const CQ_HEAD: usize = 24;
+const CQ_TAIL: usize = 28;
+const CQ_RING_MASK: usize = 32;
+const CQ_RING_ENTRIES: usize = 36;
+const CQ_OVERFLOW: usize = 40;
+const CQ_FLAGS: usize = 44;
+const CQES: usize = 64;
+
+pub(super) struct Rings {
+    ring_vmo: Vmo<Rights>,
+    sqes_vmo: Vmo<Rights>,
+    sq_entries: u32,
+    cq_entries: u32,
+    /// The offset of the SQ index array in `ring_vmo`.
+    sq_array: usize,
+}
+
+impl Rings {
+    /// Creates the rings.
+    ///
+    /// Both `sq_entries` and `cq_entries` must be powers of two.
+    pub(super) fn new(sq_entries: u32, cq_entries: u32) -> Result<Self> {
+        debug_assert!(sq_entries.is_power_of_two() && cq_entries.is_power_of_two());
+
+        let sq_array = CQES + cq_entries as usize * size_of::<IoUringCqe>();
+        let ring_size = (sq_array + sq_entries as usize * size_of::<u32>()).align_up(PAGE_SIZE);
+        let sqes_size = (sq_entries as usize * size_of::<IoUringSqe>()).align_up(PAGE_SIZE);
+
+        let ring_vmo = VmoOptions::<Rights>::new(ring_size).alloc()?;
+        let sqes_vmo = VmoOptions::<Rights>::new(sqes_size).alloc()?;
+
+        ring_vmo.write_val(SQ_RING_MASK, &(sq_entries - 1))?;
+        ring_vmo.write_val(SQ_RING_ENTRIES, &sq_entries)?;
+        ring_vmo.write_val(CQ_RING_MASK, &(cq_entries - 1))?;
+        ring_vmo.write_val(CQ_RING_ENTRIES, &cq_entries)?;
+
+        Ok(Self {
+            ring_vmo,
+            sqes_vmo,
+            sq_entries,
+            cq_entries,
+            sq_array,
+        })
+    }
+
+    /// Returns the VMO that contains the SQ ring, the CQ ring and the CQE array.
+    pub(super) fn ring_vmo(&self) -> &Vmo<Rights> {
+        &self.ring_vmo
+    }
+
+    /// Returns the VMO that contains the SQE array.
+    pub(super) fn sqes_vmo(&self) -> &Vmo<Rights> {
+        &self.sqes_vmo
+    }
+
+    pub(super) fn cq_entries(&self) -> u32 {
+        self.cq_entries
+    }
+
+    pub(super) fn sq_offsets(&self) -> IoSqringOffsets {
+        IoSqringOffsets {
+            head: SQ_HEAD as u32,
+            tail: SQ_TAIL as u32,
+            ring_mask: SQ_RING_MASK as u32,
+            ring_entries: SQ_RING_ENTRIES as u32,
+            flags: SQ_FLAGS as u32,
+            dropped: SQ_DROPPED as u32,
+            array: self.sq_array as u32,
+            resv1: 0,
+            user_addr: 0,
+        }
+    }
+
+    pub(super) fn cq_offsets(&self) -> IoCqringOffsets {
+        IoCqringOffsets {
+            head: CQ_HEAD as u32,
+            tail: CQ_TAIL as u32,
+            ring_mask: CQ_RING_MASK as u32,
+            ring_entries: CQ_RING_ENTRIES as u32,
+            overflow: CQ_OVERFLOW as u32,
+            cqes: CQES as u32,
+            flags: CQ_FLAGS as u32,
+            resv1: 0,
+            user_addr: 0,
+        }
+    }
+
+    /// Pops an SQE from the SQ ring.
+    ///
+    /// Returns `None` if the SQ ring is empty. Invalid indexes in the SQ ring are skipped and
+    /// counted as dropped.
+    ///
+    /// This method must be called with the submission lock held.
+    pub(super) fn pop_sqe(&self) -> Result<Option<IoUringSqe>> {
+        let mut head: u32 = self.ring_vmo.read_val(SQ_HEAD)?;
+        let tail: u32 = self.ring_vmo.read_val(SQ_TAIL)?;
+        // Read the SQ entries only after reading the tail written by the user space.
+        fence(Ordering::Acquire);
+
+        while head != tail {
+            let index_offset = self.sq_array + (head & (self.sq_entries - 1)) as usize * 4;
+            let index: u32 = self.ring_vmo.read_val(index_offset)?;
+            let sqe = if index < self.sq_entries {
+                let sqe_offset = index as usize * size_of::<IoUringSqe>();
+                Some(self.sqes_vmo.read_val::<IoUringSqe>(sqe_offset)?)
+            } else {
+                let dropped: u32 = self.ring_vmo.read_val(SQ_DROPPED)?;
+                self.ring_vmo
+                    .write_val(SQ_DROPPED, &dropped.wrapping_add(1))?;
+                None
+            };
+
+            head = head.wrapping_add(1);
+            // Publish the new head only after the SQE has been consumed.
+            fence(Ordering::Release);
+            self.ring_vmo.write_val(SQ_HEAD, &head)?;
+
+            if sqe.is_some() {
+                return Ok(sqe);
+            }
+        }
+
+        Ok(None)
+    }
+
+    /// Posts a CQE to the CQ ring.
+    ///
+    /// Returns `false` if the CQ ring is full.
+    ///
+    /// This method must be called with the completion lock held.
+    pub(super) fn post_cqe(&self, cqe: &IoUringCqe) -> Result<bool> {
+        let head: u32 = self.ring_vmo.read_val(CQ_HEAD)?;
+        let tail: u32 = self.ring_vmo.read_val(CQ_TAIL)?;
+        if tail.wrapping_sub(head) >= self.cq_entries {
+            return Ok(false);
+        }
+
+        let cqe_offset = CQES + (tail & (self.cq_entries - 1)) as usize * size_of::<IoUringCqe>();
+        self.ring_vmo.write_val(cqe_offset, cqe)?;
+        // Publish the new tail only after the CQE has been written.
+        fence(Ordering::Release);
+        self.ring_vmo.write_val(CQ_TAIL, &tail.wrapping_add(1))?;
+
+        Ok(true)
+    }
+
+    /// Returns the number of CQEs that have not been consumed by the user space.
+    pub(super) fn num_ready_cqes(&self) -> Result<u32> {
+        let head: u32 = self.ring_vmo.read_val(CQ_HEAD)?;
+        let tail: u32 = self.ring_vmo.read_val(CQ_TAIL)?;
+        Ok(tail.wrapping_sub(head))
+    }
+
+    /// Returns whether the CQ ring is full.
+    pub(super) fn is_cq_full(&self) -> Result<bool> {
+        Ok(self.num_ready_cqes()? >= self.cq_entries)
+    }
+}
@@ -0,0 +1,181 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! The user-space ABI of io_uring.
+//!
+//! Reference: <https://elixir.bootlin.com/linux/v6.11/source/include/uapi/linux/io_uring.h>.
+
+#![allow(non_camel_case_types)]
+
+use crate::prelude::*;
+
+/// The offset to map the SQ ring.
+pub const IORING_OFF_SQ_RING: usize = 0;
+/// The offset to map the CQ ring.
+pub const IORING_OFF_CQ_RING: usize = 0x8000000;
+/// The offset to map the SQE array.
+pub const IORING_OFF_SQES: usize = 0x10000000;
+
+/// The maximum number of SQ entries.
+pub const IORING_MAX_ENTRIES: u32 = 32768;
+/// The maximum number of CQ entries.
+pub const IORING_MAX_CQ_ENTRIES: u32 = 2 * IORING_MAX_ENTRIES;
+
+bitflags! {
+    /// The flags of `io_uring_setup`.
+    pub struct IoUringSetupFlags: u32 {
+        const IORING_SETUP_IOPOLL = 1 << 0;
+        const IORING_SETUP_SQPOLL = 1 << 1;
+        const IORING_SETUP_SQ_AFF = 1 << 2;
+        const IORING_SETUP_CQSIZE = 1 << 3;
+        const IORING_SETUP_CLAMP = 1 << 4;
+    }
+}
+
+bitflags! {
+    /// The features reported by `io_uring_setup`.
+    pub struct IoUringFeatures: u32 {
+        const IORING_FEAT_SINGLE_MMAP = 1 << 0;
+        const IORING_FEAT_NODROP = 1 << 1;
+        const IORING_FEAT_SUBMIT_STABLE = 1 << 2;
+    }
+}
+
+bitflags! {
+    /// The flags of `io_uring_enter`.
+    pub struct IoUringEnterFlags: u32 {
+        const IORING_ENTER_GETEVENTS = 1 << 0;
+        const IORING_ENTER_SQ_WAKEUP = 1 << 1;
+        const IORING_ENTER_SQ_WAIT = 1 << 2;
+        const IORING_ENTER_EXT_ARG = 1 << 3;
+    }
+}
+
+bitflags! {
+    /// The flags of an SQE.
+    pub struct IoSqeFlags: u8 {
+        const IOSQE_FIXED_FILE = 1 << 0;
+        const IOSQE_IO_DRAIN = 1 << 1;
+        const IOSQE_IO_LINK = 1 << 2;
+        const IOSQE_IO_HARDLINK = 1 << 3;
+        const IOSQE_ASYNC = 1 << 4;
+        const IOSQE_BUFFER_SELECT = 1 << 5;
+        const IOSQE_CQE_SKIP_SUCCESS = 1 << 6;
+    }
+}
+
+bitflags! {
+    /// The flags of `IORING_OP_TIMEOUT`.
+    pub struct IoTimeoutFlags: u32 {
+        const IORING_TIMEOUT_ABS = 1 << 0;
+    }
+}
+
+/// The opcodes of SQEs.
+#[repr(u8)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, TryFromInt)]
+pub enum IoUringOp {
+    IORING_OP_NOP = 0,
+    IORING_OP_READV = 1,
+    IORING_OP_WRITEV = 2,
+    IORING_OP_FSYNC = 3,
+    IORING_OP_READ_FIXED = 4,
+    IORING_OP_WRITE_FIXED = 5,
+    IORING_OP_POLL_ADD = 6,
+    IORING_OP_POLL_REMOVE = 7,
+    IORING_OP_SYNC_FILE_RANGE = 8,
+    IORING_OP_SENDMSG = 9,
+    IORING_OP_RECVMSG = 10,
+    IORING_OP_TIMEOUT = 11,
+    IORING_OP_TIMEOUT_REMOVE = 12,
+    IORING_OP_ACCEPT = 13,
+    IORING_OP_ASYNC_CANCEL = 14,
+    IORING_OP_LINK_TIMEOUT = 15,
+    IORING_OP_CONNECT = 16,
+    IORING_OP_FALLOCATE = 17,
+    IORING_OP_OPENAT = 18,
+    IORING_OP_CLOSE = 19,
+    IORING_OP_FILES_UPDATE = 20,
+    IORING_OP_STATX = 21,
+    IORING_OP_READ = 22,
+    IORING_OP_WRITE = 23,
+    IORING_OP_FADVISE = 24,
+    IORING_OP_MADVISE = 25,
+    IORING_OP_SEND = 26,
+    IORING_OP_RECV = 27,
+}
+
+/// A submission queue entry (SQE).
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Pod)]
+pub struct IoUringSqe {
+    pub opcode: u8,
+    pub flags: u8,
+    pub ioprio: u16,
+    pub fd: i32,
+    /// The file offset, or `addr2` for some opcodes.
+    pub off: u64,
+    /// The buffer address.
+    pub addr: u64,
+    pub len: u32,
+    /// The opcode-specific flags (e.g., `rw_flags`, `msg_flags`, and `timeout_flags`).
+    pub op_flags: u32,
+    pub user_data: u64,
+    pub buf_index: u16,
+    pub personality: u16,
+    pub splice_fd_in: i32,
+    pub addr3: u64,
+    pub pad2: u64,
+}
+
+/// A completion queue entry (CQE).
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Pod)]
+pub struct IoUringCqe {
+    pub user_data: u64,
+    pub res: i32,
+    pub flags: u32,
+}
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Pod)]
+pub struct IoSqringOffsets {
+    pub head: u32,
+    pub tail: u32,
+    pub ring_mask: u32,
+    pub ring_entries: u32,
+    pub flags: u32,
+    pub dropped: u32,
+    pub array: u32,
+    pub resv1: u32,
+    pub user_addr: u64,
+}
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Pod)]
+pub struct IoCqringOffsets {
+    pub head: u32,
+    pub tail: u32,
+    pub ring_mask: u32,
+    pub ring_entries: u32,
+    pub overflow: u32,
+    pub cqes: u32,
+    pub flags: u32,
+    pub resv1: u32,
+    pub user_addr: u64,
+}
+
+/// The parameters of `io_uring_setup`.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Pod)]
+pub struct IoUringParams {
+    pub sq_entries: u32,
+    pub cq_entries: u32,
+    pub flags: u32,
+    pub sq_thread_cpu: u32,
+    pub sq_thread_idle: u32,
+    pub features: u32,
+    pub wq_fd: u32,
+    pub resv: [u32; 3],
+    pub sq_off: IoSqringOffsets,
+    pub cq_off: IoCqringOffsets,
+}
@@ -0,0 +1,123 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! The worker threads that execute the I/O requests.
+
+use core::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+
+use ostd::sync::WaitQueue;
+
+use crate::{
+    prelude::*,
+    thread::{
+        kernel_thread::{AsKernelThread, ThreadOptions},
+        Thread,
+    },
+};
+
+/// The maximum number of worker threads of an io_uring instance.
+const MAX_WORKERS: usize = 16;
+
+type Work = Box<dyn FnOnce() + Send>;
+
+/// A pool of kernel threads that execute the I/O requests of an io_uring instance.
+///
+/// The worker threads are spawned on demand. Once spawned, a worker thread stays idle while the
+/// queue is empty, and exits when the pool is shut down.
+pub(super) struct IoWorkers {
+    queue: Mutex<VecDeque<Work>>,
+    wait_queue: WaitQueue,
+    threads: Mutex<Vec<Weak<Thread>>>,
+    num_workers: AtomicUsize,
+    num_idle: AtomicUsize,
+    is_shutdown: AtomicBool,
+}
+
+impl IoWorkers {
+    pub(super) fn new() -> Arc<Self> {
+        Arc::new(Self {
+            queue: Mutex::new(VecDeque::new()),
+            wait_queue: WaitQueue::new(),
+            threads: Mutex::new(Vec::new()),
+            num_workers: AtomicUsize::new(0),
+            num_idle: AtomicUsize::new(0),
+            is_shutdown: AtomicBool::new(false),
+        })
+    }
+
+    /// Submits a piece of work to the pool.
+    ///
+    /// A new worker thread is spawned if there are not enough idle workers to pick up the
+    /// pending work, unless the number of worker threads has reached the limit.
+    pub(super) fn submit(self: &Arc<Self>, work: Work) {
+        let num_pending = {
+            let mut queue = self.queue.lock();
+            queue.push_back(work);
+            queue.len()
+        };
+
+        let should_spawn = num_pending > self.num_idle.load(Ordering::Relaxed)
+            && self
+                .num_workers
+                .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |num| {
+                    (num < MAX_WORKERS).then_some(num + 1)
+                })
+                .is_ok();
+        if should_spawn {
+            let workers = self.clone();
+            let thread = ThreadOptions::new(move || workers.run())
+                .name("iou-wrk")
+                .spawn();
+
+            let mut threads = self.threads.lock();
+            threads.push(Arc::downgrade(&thread));
+            // The pool may be shut down before the thread is recorded.
+            if self.is_shutdown() {
+                thread.as_kernel_thread().unwrap().stop();
+            }
+        }
+
+        self.wait_queue.wake_one();
+    }
+
+    /// Shuts down the pool.
+    ///
+    /// The pending work is dropped. The work that is being executed is canceled, i.e., the worker
+    /// threads are requested to stop, so that the work fails with `EINTR` instead of waiting any
+    /// longer (see [`Pause`]). The worker threads exit after the work returns.
+    ///
+    /// [`Pause`]: crate::process::signal::Pause
+    pub(super) fn shutdown(&self) {
+        self.is_shutdown.store(true, Ordering::Relaxed);
+        self.queue.lock().clear();
+        self.wait_queue.wake_all();
+
+        for thread in self.threads.lock().iter().filter_map(Weak::upgrade) {
+            thread.as_kernel_thread().unwrap().stop();
+        }
+    }
+
+    /// Returns whether the pool is shut down.
+    pub(super) fn is_shutdown(&self) -> bool {
+        self.is_shutdown.load(Ordering::Relaxed)
+    }
+
+    fn run(&self) {
+        loop {
+            self.num_idle.fetch_add(1, Ordering::Relaxed);
+            let work = self.wait_queue.wait_until(|| {
+                if self.is_shutdown() {
+                    return Some(None);
+                }
+                self.queue.lock().pop_front().map(Some)
+            });
+            self.num_idle.fetch_sub(1, Ordering::Relaxed);
+
+            let Some(work) = work else {
+                break;
+            };
+            work();
+        }
+
+        self.num_workers.fetch_sub(1, Ordering::Relaxed);
+    }
+}
@@ -1,4 +1,6 @@
 // SPDX-License-Identifier: MPL-2.0
+pub mod binfmt_misc;
+pub mod cgroupfs;
 pub mod device;
 pub mod devpts;
 pub mod epoll;
@@ -7,15 +9,19 @@ pub mod ext2;
 pub mod file_handle;
 pub mod file_table;
 pub mod fs_resolver;
+pub mod fscrypt;
 pub mod inode_handle;
+pub mod io_uring;
 pub mod named_pipe;
 pub mod path;
 pub mod pipe;
 pub mod procfs;
 pub mod ramfs;
 pub mod rootfs;
+pub mod splice;
 pub mod thread_info;
 pub mod utils;
+mod verity;
 
 use aster_block::BlockDevice;
 use aster_virtio::device::block::device::BlockDevice as VirtIoBlockDevice;
@@ -52,6 +58,8 @@ pub fn lazy_init() {
     let exfat_device_name = "vexfat";
 
     if let Ok(block_device_ext2) = start_block_device(ext2_device_name) {
+        let block_device_ext2 =
+            verity::verify_if_specified(ext2_device_name, block_device_ext2).unwrap();
         let ext2_fs = Ext2::open(block_device_ext2).unwrap();
         let target_path = FsPath::try_from("/ext2").unwrap();
         println!("[kernel] Mount Ext2 fs at {:?} ", target_path);
@@ -59,6 +67,8 @@ pub fn lazy_init() {
     }
 
     if let Ok(block_device_exfat) = start_block_device(exfat_device_name) {
+        let block_device_exfat =
+            verity::verify_if_specified(exfat_device_name, block_device_exfat).unwrap();
         let exfat_fs = ExfatFS::open(block_device_exfat, ExfatMountOptions::default()).unwrap();
         let target_path = FsPath::try_from("/exfat").unwrap();
         println!("[kernel] Mount ExFat fs at {:?} ", target_path);
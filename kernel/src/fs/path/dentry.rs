@@ -21,6 +21,7 @@ use crate::{
     },
     prelude::*,
     process::{Gid, Uid},
+    security,
 };
 
 /// A `Dentry` is used to represent a location in the mount tree.
@@ -36,7 +37,11 @@ pub struct Dentry_ {
     inode: Arc<dyn Inode>,
     name_and_parent: RwLock<Option<(String, Arc<Dentry_>)>>,
     children: RwMutex<Children>,
-    flags: AtomicU32,
+    /// The number of mount nodes that are mounted on this `Dentry_`.
+    ///
+    /// A `Dentry_` can be shared by multiple mount nodes (e.g., due to bind mounts
+    /// or mount namespaces), so several mounts may use it as their mountpoint.
+    mount_count: AtomicU32,
     this: Weak<Dentry_>,
 }
 
@@ -52,7 +57,7 @@ impl Dentry_ {
     fn new(inode: Arc<dyn Inode>, options: DentryOptions) -> Arc<Self> {
         Arc::new_cyclic(|weak_self| Self {
             inode,
-            flags: AtomicU32::new(DentryFlags::empty().bits()),
+            mount_count: AtomicU32::new(0),
             name_and_parent: match options {
                 DentryOptions::Leaf(name_and_parent) => RwLock::new(Some(name_and_parent)),
                 _ => RwLock::new(None),
@@ -101,11 +106,6 @@ impl Dentry_ {
         &self.inode
     }
 
-    fn flags(&self) -> DentryFlags {
-        let flags = self.flags.load(Ordering::Relaxed);
-        DentryFlags::from_bits(flags).unwrap()
-    }
-
     /// Checks if this dentry is a descendant (child, grandchild, or
     /// great-grandchild, etc.) of another dentry.
     pub fn is_descendant_of(&self, ancestor: &Arc<Self>) -> bool {
@@ -120,17 +120,19 @@ impl Dentry_ {
     }
 
     pub fn is_mountpoint(&self) -> bool {
-        self.flags().contains(DentryFlags::MOUNTED)
+        self.mount_count.load(Ordering::Acquire) > 0
     }
 
     pub fn set_mountpoint_dentry(&self) {
-        self.flags
-            .fetch_or(DentryFlags::MOUNTED.bits(), Ordering::Release);
+        self.mount_count.fetch_add(1, Ordering::Release);
     }
 
     pub fn clear_mountpoint(&self) {
-        self.flags
-            .fetch_and(!(DentryFlags::MOUNTED.bits()), Ordering::Release);
+        let _ = self
+            .mount_count
+            .fetch_update(Ordering::Release, Ordering::Relaxed, |count| {
+                count.checked_sub(1)
+            });
     }
 
     /// Currently, the root `Dentry_` of a fs is the root of a mount.
@@ -335,7 +337,7 @@ impl Debug for Dentry_ {
     fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
         f.debug_struct("Dentry_")
             .field("inode", &self.inode)
-            .field("flags", &self.flags())
+            .field("mount_count", &self.mount_count.load(Ordering::Relaxed))
             .finish()
     }
 }
@@ -364,12 +366,6 @@ impl DentryKey {
     }
 }
 
-bitflags! {
-    struct DentryFlags: u32 {
-        const MOUNTED = 1 << 0;
-    }
-}
-
 enum DentryOptions {
     Root,
     Leaf((String, Arc<Dentry_>)),
@@ -470,11 +466,12 @@ impl Dentry {
         {
             return_errno!(Errno::EACCES);
         }
+        security::path_mknod(self, type_)?;
         let new_child_dentry = self.inner.create(name, type_, mode)?;
         Ok(Self::new(self.mount_node.clone(), new_child_dentry))
     }
 
-    fn new(mount_node: Arc<MountNode>, inner: Arc<Dentry_>) -> Self {
+    pub(super) fn new(mount_node: Arc<MountNode>, inner: Arc<Dentry_>) -> Self {
         Self { mount_node, inner }
     }
 
@@ -536,7 +533,7 @@ impl Dentry {
     /// If it is the root of a mount, it will go up to the mountpoint
     /// to get the name of the mountpoint recursively.
     fn effective_name(&self) -> String {
-        if !self.inner.is_root_of_mount() {
+        if !self.is_root_of_mount() {
             return self.inner.name();
         }
 
@@ -558,8 +555,8 @@ impl Dentry {
     ///
     /// If it is the root of a mount, it will go up to the mountpoint
     /// to get the parent of the mountpoint recursively.
-    fn effective_parent(&self) -> Option<Self> {
-        if !self.inner.is_root_of_mount() {
+    pub fn effective_parent(&self) -> Option<Self> {
+        if !self.is_root_of_mount() {
             return Some(Self::new(
                 self.mount_node.clone(),
                 self.inner.parent().unwrap(),
@@ -625,7 +622,7 @@ impl Dentry {
     ///
     /// Note that the root mount cannot be unmounted.
     pub fn unmount(&self) -> Result<Arc<MountNode>> {
-        if !self.inner.is_root_of_mount() {
+        if !self.is_root_of_mount() {
             return_errno_with_message!(Errno::EINVAL, "not mounted");
         }
 
@@ -643,6 +640,7 @@ impl Dentry {
 
     /// Creates a `Dentry` by making an inode of the `type_` with the `mode`.
     pub fn mknod(&self, name: &str, mode: InodeMode, type_: MknodType) -> Result<Self> {
+        security::path_mknod(self, type_.inode_type())?;
         let inner = self.inner.mknod(name, mode, type_)?;
         Ok(Self::new(self.mount_node.clone(), inner))
     }
@@ -652,16 +650,19 @@ impl Dentry {
         if !Arc::ptr_eq(&old.mount_node, &self.mount_node) {
             return_errno_with_message!(Errno::EXDEV, "cannot cross mount");
         }
+        security::path_link(old, self)?;
         self.inner.link(&old.inner, name)
     }
 
     /// Deletes a `Dentry`.
     pub fn unlink(&self, name: &str) -> Result<()> {
+        security::path_unlink(self, name)?;
         self.inner.unlink(name)
     }
 
     /// Deletes a directory `Dentry`.
     pub fn rmdir(&self, name: &str) -> Result<()> {
+        security::path_rmdir(self, name)?;
         self.inner.rmdir(name)
     }
 
@@ -670,6 +671,7 @@ impl Dentry {
         if !Arc::ptr_eq(&self.mount_node, &new_dir.mount_node) {
             return_errno_with_message!(Errno::EXDEV, "cannot cross mount");
         }
+        security::path_rename(self, old_name, new_dir, new_name)?;
         self.inner.rename(old_name, &new_dir.inner, new_name)
     }
 
@@ -681,7 +683,7 @@ impl Dentry {
     pub fn bind_mount_to(&self, dst_dentry: &Self, recursive: bool) -> Result<()> {
         let src_mount = self
             .mount_node
-            .clone_mount_node_tree(&self.inner, recursive);
+            .clone_mount_node_tree(&self.inner, recursive)?;
         src_mount.graft_mount_node_tree(dst_dentry)?;
         Ok(())
     }
@@ -694,6 +696,18 @@ impl Dentry {
     pub fn mount_node(&self) -> &Arc<MountNode> {
         &self.mount_node
     }
+
+    /// Checks whether the `Dentry` is the root of its mount node.
+    ///
+    /// Note that the root of a bind mount may not be the root of the file system.
+    pub fn is_root_of_mount(&self) -> bool {
+        Arc::ptr_eq(&self.inner, self.mount_node.root_dentry())
+    }
+
+    /// Gets the inner `Dentry_`.
+    pub(super) fn inner(&self) -> &Arc<Dentry_> {
+        &self.inner
+    }
 }
 
 #[inherit_methods(from = "self.inner")]
@@ -719,6 +733,5 @@ impl Dentry {
     pub fn set_ctime(&self, time: Duration);
     pub fn key(&self) -> DentryKey;
     pub fn inode(&self) -> &Arc<dyn Inode>;
-    pub fn is_root_of_mount(&self) -> bool;
     pub fn is_mountpoint(&self) -> bool;
 }
@@ -3,7 +3,9 @@
 //! Form file paths within and across FSes with dentries and mount points.
 
 pub use dentry::{Dentry, DentryKey};
-pub use mount::MountNode;
+pub use mount::{MountNode, MountPropagation, PeerGroup, PropagationType};
+pub use mount_namespace::MountNamespace;
 
 mod dentry;
 mod mount;
+mod mount_namespace;
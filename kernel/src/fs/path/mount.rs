@@ -1,5 +1,7 @@
 // SPDX-License-Identifier: MPL-2.0
 
+use core::sync::atomic::{AtomicU32, Ordering};
+
 use hashbrown::HashMap;
 
 use crate::{
@@ -12,6 +14,8 @@ use crate::{
 
 /// The `MountNode` is used to form a mount tree to maintain the mount information.
 pub struct MountNode {
+    /// The unique ID of the mount node, as reported in `/proc/[pid]/mountinfo`.
+    id: u32,
     /// Root dentry.
     root_dentry: Arc<Dentry_>,
     /// Mountpoint dentry. A mount node can be mounted on one dentry of another mount node,
@@ -23,6 +27,8 @@ pub struct MountNode {
     parent: RwLock<Option<Weak<MountNode>>>,
     /// Child mount nodes which are mounted on one dentry of self.
     children: RwLock<HashMap<DentryKey, Arc<Self>>>,
+    /// How mount and unmount events are propagated to and from this mount node.
+    propagation: RwLock<MountPropagation>,
     /// Reference to self.
     this: Weak<Self>,
 }
@@ -49,10 +55,12 @@ impl MountNode {
     /// mount nodes must be explicitly assigned a mountpoint to maintain structural integrity.
     fn new(fs: Arc<dyn FileSystem>, parent_mount: Option<Weak<MountNode>>) -> Arc<Self> {
         Arc::new_cyclic(|weak_self| Self {
+            id: alloc_mount_id(),
             root_dentry: Dentry_::new_root(fs.root_inode()),
             mountpoint_dentry: RwLock::new(None),
             parent: RwLock::new(parent_mount),
             children: RwLock::new(HashMap::new()),
+            propagation: RwLock::new(MountPropagation::Private),
             fs,
             this: weak_self.clone(),
         })
@@ -68,6 +76,10 @@ impl MountNode {
     /// It is allowed to mount a fs even if the fs has been provided to another
     /// mountpoint. It is the fs's responsibility to ensure the data consistency.
     ///
+    /// If this mount node is shared, the new mount is propagated to all its peers
+    /// and slaves whose subtree contains the mountpoint, unless they already have a
+    /// mount on the mountpoint.
+    ///
     /// Return the mounted child mount.
     pub fn mount(&self, fs: Arc<dyn FileSystem>, mountpoint: &Dentry) -> Result<Arc<Self>> {
         if !Arc::ptr_eq(mountpoint.mount_node(), &self.this()) {
@@ -80,11 +92,15 @@ impl MountNode {
         let key = mountpoint.key();
         let child_mount = Self::new(fs, Some(Arc::downgrade(mountpoint.mount_node())));
         self.children.write().insert(key, child_mount.clone());
+        self.propagate_mount(&child_mount, mountpoint.inner());
         Ok(child_mount)
     }
 
     /// Unmounts a child mount node from the mountpoint and returns it.
     ///
+    /// If this mount node is shared, the corresponding mounts of its peers
+    /// and slaves are unmounted as well.
+    ///
     /// The mountpoint should belong to this mount node, or an error is returned.
     pub fn unmount(&self, mountpoint: &Dentry) -> Result<Arc<Self>> {
         if !Arc::ptr_eq(mountpoint.mount_node(), &self.this()) {
@@ -96,19 +112,127 @@ impl MountNode {
             .write()
             .remove(&mountpoint.key())
             .ok_or_else(|| Error::with_message(Errno::ENOENT, "can not find child mount"))?;
+        child_mount.leave_peer_group();
+        self.propagate_unmount(&child_mount, mountpoint.inner());
         Ok(child_mount)
     }
 
+    /// Propagates a newly created child mount to the receivers of this mount node.
+    ///
+    /// All the propagated copies, together with the original child mount, form a
+    /// peer group so that the mounts beneath them will be propagated as well. If the
+    /// child mount is already shared (e.g., a bind mount of a shared mount), its peer
+    /// group is reused.
+    fn propagate_mount(&self, child_mount: &Arc<Self>, mountpoint: &Arc<Dentry_>) {
+        let receivers = self.propagation_receivers();
+        if receivers.is_empty() {
+            return;
+        }
+
+        let peer_group = match child_mount.propagation() {
+            MountPropagation::Shared(peer_group) => peer_group,
+            _ => {
+                let peer_group = PeerGroup::new();
+                child_mount.leave_peer_group();
+                child_mount.join_peer_group(&peer_group);
+                peer_group
+            }
+        };
+        for (receiver, is_slave) in receivers {
+            if !receiver.covers(mountpoint) {
+                continue;
+            }
+
+            // Mounts cannot be stacked on the same mountpoint. The receivers that already have a
+            // mount on the mountpoint are skipped, so that their existing mounts stay visible.
+            if receiver.children.read().contains_key(&mountpoint.key()) {
+                continue;
+            }
+
+            // The submounts (e.g., of a recursive bind mount) are propagated together with the
+            // child mount. The copy of the child mount joins `peer_group` like the child mount.
+            // The copy is made before locking the receiver, which may be in the copied tree.
+            let new_child_mount = child_mount.clone_mount_node_tree_with(
+                child_mount.root_dentry(),
+                true,
+                true,
+                |_, _| {},
+            );
+
+            let mut receiver_children = receiver.children.write();
+            if receiver_children.contains_key(&mountpoint.key()) {
+                continue;
+            }
+            new_child_mount.set_parent(&receiver);
+            new_child_mount.set_mountpoint_dentry(mountpoint);
+            mountpoint.set_mountpoint_dentry();
+            receiver_children.insert(mountpoint.key(), new_child_mount.clone());
+            drop(receiver_children);
+
+            if is_slave {
+                new_child_mount.leave_peer_group();
+                new_child_mount.become_slave_of(&peer_group);
+            }
+        }
+    }
+
+    /// Propagates an unmount event to the receivers of this mount node.
+    ///
+    /// Only the mounts that are propagated copies of `child_mount` are removed.
+    fn propagate_unmount(&self, child_mount: &Arc<Self>, mountpoint: &Arc<Dentry_>) {
+        let key = mountpoint.key();
+        for (receiver, _) in self.propagation_receivers() {
+            let mut children = receiver.children.write();
+            let Some(receiver_child) = children.get(&key) else {
+                continue;
+            };
+            if !Arc::ptr_eq(&receiver_child.fs, &child_mount.fs)
+                || !Arc::ptr_eq(&receiver_child.root_dentry, &child_mount.root_dentry)
+            {
+                continue;
+            }
+            let receiver_child = children.remove(&key).unwrap();
+            drop(children);
+            receiver_child.leave_peer_group();
+            mountpoint.clear_mountpoint();
+        }
+    }
+
+    /// Returns the mount nodes that receive propagation events from this mount node.
+    ///
+    /// The boolean in each pair tells whether the receiver is a slave of this mount node.
+    fn propagation_receivers(&self) -> Vec<(Arc<Self>, bool)> {
+        let peer_group = match &*self.propagation.read() {
+            MountPropagation::Shared(peer_group) => peer_group.clone(),
+            _ => return Vec::new(),
+        };
+
+        let peers = peer_group
+            .members()
+            .into_iter()
+            .filter(|peer| !Arc::ptr_eq(peer, &self.this()))
+            .map(|peer| (peer, false));
+        let slaves = peer_group.slaves().into_iter().map(|slave| (slave, true));
+        peers.chain(slaves).collect()
+    }
+
+    /// Checks whether the `dentry` is visible in the subtree of this mount node.
+    fn covers(&self, dentry: &Arc<Dentry_>) -> bool {
+        Arc::ptr_eq(dentry, &self.root_dentry) || dentry.is_descendant_of(&self.root_dentry)
+    }
+
     /// Clones a mount node with the an root `Dentry_`.
     ///
     /// The new mount node will have the same fs as the original one and
     /// have no parent and children. We should set the parent and children manually.
     fn clone_mount_node(&self, root_dentry: &Arc<Dentry_>) -> Arc<Self> {
         Arc::new_cyclic(|weak_self| Self {
+            id: alloc_mount_id(),
             root_dentry: root_dentry.clone(),
             mountpoint_dentry: RwLock::new(None),
             parent: RwLock::new(None),
             children: RwLock::new(HashMap::new()),
+            propagation: RwLock::new(MountPropagation::Private),
             fs: self.fs.clone(),
             this: weak_self.clone(),
         })
@@ -122,12 +246,44 @@ impl MountNode {
     ///
     /// If `recursive` is set to `true`, the entire tree will be copied.
     /// Otherwise, only the root mount node will be copied.
+    ///
+    /// The new mount nodes inherit the propagation type of the original ones,
+    /// except that the unbindable mounts (and their subtrees) are skipped.
     pub(super) fn clone_mount_node_tree(
         &self,
         root_dentry: &Arc<Dentry_>,
         recursive: bool,
+    ) -> Result<Arc<Self>> {
+        if matches!(*self.propagation.read(), MountPropagation::Unbindable) {
+            return_errno_with_message!(Errno::EINVAL, "the mount is unbindable");
+        }
+
+        let new_root_mount =
+            self.clone_mount_node_tree_with(root_dentry, recursive, true, |_, _| {});
+        Ok(new_root_mount)
+    }
+
+    /// Copies the whole mount tree rooted at this mount node.
+    ///
+    /// Unlike [`Self::clone_mount_node_tree`], no mount node is skipped. The
+    /// `on_copy` callback is invoked with each pair of original and copied mount nodes.
+    pub(super) fn copy_mount_node_tree(
+        &self,
+        on_copy: impl FnMut(&Arc<Self>, &Arc<Self>),
+    ) -> Arc<Self> {
+        self.clone_mount_node_tree_with(&self.root_dentry, true, false, on_copy)
+    }
+
+    fn clone_mount_node_tree_with(
+        &self,
+        root_dentry: &Arc<Dentry_>,
+        recursive: bool,
+        skip_unbindable: bool,
+        mut on_copy: impl FnMut(&Arc<Self>, &Arc<Self>),
     ) -> Arc<Self> {
         let new_root_mount = self.clone_mount_node(root_dentry);
+        new_root_mount.inherit_propagation_from(self);
+        on_copy(&self.this(), &new_root_mount);
         if !recursive {
             return new_root_mount;
         }
@@ -139,7 +295,15 @@ impl MountNode {
             let old_children = old_mount.children.read();
             for old_child_mount in old_children.values() {
                 let mountpoint_dentry = old_child_mount.mountpoint_dentry().unwrap();
-                if !mountpoint_dentry.is_descendant_of(old_mount.root_dentry()) {
+                if !new_parent_mount.covers(&mountpoint_dentry) {
+                    continue;
+                }
+                if skip_unbindable
+                    && matches!(
+                        *old_child_mount.propagation.read(),
+                        MountPropagation::Unbindable
+                    )
+                {
                     continue;
                 }
                 let new_child_mount =
@@ -150,8 +314,10 @@ impl MountNode {
                     .write()
                     .insert(key, new_child_mount.clone());
                 new_child_mount.set_parent(&new_parent_mount);
-                new_child_mount
-                    .set_mountpoint_dentry(&old_child_mount.mountpoint_dentry().unwrap());
+                new_child_mount.set_mountpoint_dentry(&mountpoint_dentry);
+                mountpoint_dentry.set_mountpoint_dentry();
+                new_child_mount.inherit_propagation_from(old_child_mount);
+                on_copy(old_child_mount, &new_child_mount);
                 stack.push(old_child_mount.clone());
                 new_stack.push(new_child_mount);
             }
@@ -164,14 +330,16 @@ impl MountNode {
     fn detach_mount_node(&self) {
         if let Some(parent) = self.parent() {
             let parent = parent.upgrade().unwrap();
-            parent
-                .children
-                .write()
-                .remove(&self.mountpoint_dentry().unwrap().key());
+            let mountpoint_dentry = self.mountpoint_dentry().unwrap();
+            parent.children.write().remove(&mountpoint_dentry.key());
+            mountpoint_dentry.clear_mountpoint();
         }
     }
 
     /// Attaches the mount node to the mountpoint.
+    ///
+    /// If the mount node of the mountpoint is shared, the attachment is
+    /// propagated in the same way as a new mount.
     fn attach_mount_node(&self, mountpoint: &Dentry) {
         let key = mountpoint.key();
         mountpoint
@@ -181,6 +349,9 @@ impl MountNode {
             .insert(key, self.this());
         self.set_parent(mountpoint.mount_node());
         mountpoint.set_mountpoint(self.this());
+        mountpoint
+            .mount_node()
+            .propagate_mount(&self.this(), mountpoint.inner());
     }
 
     /// Grafts the mount node tree to the mountpoint.
@@ -201,11 +372,110 @@ impl MountNode {
         self.children.read().get(&mountpoint.key()).cloned()
     }
 
+    /// Gets the child mount nodes of this mount node.
+    pub fn children(&self) -> Vec<Arc<Self>> {
+        self.children.read().values().cloned().collect()
+    }
+
+    /// Gets the unique ID of this mount node.
+    pub fn id(&self) -> u32 {
+        self.id
+    }
+
+    /// Gets the propagation type of this mount node.
+    pub fn propagation(&self) -> MountPropagation {
+        self.propagation.read().clone()
+    }
+
+    /// Changes the propagation type of this mount node.
+    ///
+    /// Turning a mount node into a shared one creates a new peer group containing
+    /// only this node, unless it is already shared. Turning a shared mount node into
+    /// a slave makes it receive propagation from its former peers.
+    pub fn set_propagation_type(&self, type_: PropagationType) {
+        let old_peer_group = match &*self.propagation.read() {
+            MountPropagation::Shared(peer_group) => Some(peer_group.clone()),
+            _ => None,
+        };
+
+        match type_ {
+            PropagationType::Shared => {
+                if old_peer_group.is_none() {
+                    self.join_peer_group(&PeerGroup::new());
+                }
+            }
+            PropagationType::Slave => {
+                // A slave of nothing is just a private mount.
+                self.leave_peer_group();
+                if let Some(peer_group) = old_peer_group
+                    && !peer_group.members().is_empty()
+                {
+                    self.become_slave_of(&peer_group);
+                }
+            }
+            PropagationType::Private => {
+                self.leave_peer_group();
+            }
+            PropagationType::Unbindable => {
+                self.leave_peer_group();
+                *self.propagation.write() = MountPropagation::Unbindable;
+            }
+        }
+    }
+
+    fn join_peer_group(&self, peer_group: &Arc<PeerGroup>) {
+        peer_group.members.lock().push(self.this.clone());
+        *self.propagation.write() = MountPropagation::Shared(peer_group.clone());
+    }
+
+    fn become_slave_of(&self, peer_group: &Arc<PeerGroup>) {
+        peer_group.slaves.lock().push(self.this.clone());
+        *self.propagation.write() = MountPropagation::Slave(peer_group.clone());
+    }
+
+    /// Removes this mount node from its peer group (or master group), making it private.
+    fn leave_peer_group(&self) {
+        let mut propagation = self.propagation.write();
+        match &*propagation {
+            MountPropagation::Shared(peer_group) => peer_group.remove_member(self),
+            MountPropagation::Slave(peer_group) => peer_group.remove_slave(self),
+            _ => {}
+        }
+        *propagation = MountPropagation::Private;
+    }
+
+    fn inherit_propagation_from(&self, other: &Self) {
+        match other.propagation() {
+            MountPropagation::Shared(peer_group) => self.join_peer_group(&peer_group),
+            MountPropagation::Slave(peer_group) => self.become_slave_of(&peer_group),
+            MountPropagation::Unbindable => {
+                *self.propagation.write() = MountPropagation::Unbindable;
+            }
+            MountPropagation::Private => {}
+        }
+    }
+
     /// Gets the root `Dentry_` of this mount node.
     pub fn root_dentry(&self) -> &Arc<Dentry_> {
         &self.root_dentry
     }
 
+    /// Gets the path of the root `Dentry_` relative to the root of the fs.
+    ///
+    /// The path is "/" unless this mount node is created by a bind mount.
+    pub fn root_path(&self) -> String {
+        let mut path = String::new();
+        let mut dentry = self.root_dentry.clone();
+        while let Some(parent) = dentry.parent() {
+            path = String::from("/") + &dentry.name() + &path;
+            dentry = parent;
+        }
+        if path.is_empty() {
+            path.push('/');
+        }
+        path
+    }
+
     /// Gets the mountpoint `Dentry_` of this mount node if any.
     pub fn mountpoint_dentry(&self) -> Option<Arc<Dentry_>> {
         self.mountpoint_dentry.read().clone()
@@ -258,12 +528,112 @@ impl MountNode {
     }
 }
 
+impl Drop for MountNode {
+    fn drop(&mut self) {
+        match &*self.propagation.read() {
+            MountPropagation::Shared(peer_group) => peer_group.remove_member(self),
+            MountPropagation::Slave(peer_group) => peer_group.remove_slave(self),
+            _ => {}
+        }
+    }
+}
+
 impl Debug for MountNode {
     fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
         f.debug_struct("MountNode")
+            .field("id", &self.id)
             .field("root", &self.root_dentry)
             .field("mountpoint", &self.mountpoint_dentry)
             .field("fs", &self.fs)
+            .field("propagation", &self.propagation)
             .finish()
     }
 }
+
+fn alloc_mount_id() -> u32 {
+    static NEXT_MOUNT_ID: AtomicU32 = AtomicU32::new(1);
+    NEXT_MOUNT_ID.fetch_add(1, Ordering::Relaxed)
+}
+
+/// The propagation type requested by `mount(2)` with `MS_SHARED`, `MS_PRIVATE`,
+/// `MS_SLAVE`, or `MS_UNBINDABLE`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PropagationType {
+    Shared,
+    Private,
+    Slave,
+    Unbindable,
+}
+
+/// The propagation state of a mount node.
+///
+/// Reference: <https://docs.kernel.org/filesystems/sharedsubtree.html>.
+#[derive(Debug, Clone)]
+pub enum MountPropagation {
+    /// Mount and unmount events are neither sent nor received.
+    Private,
+    /// Mount and unmount events are propagated among all the members of the peer group.
+    Shared(Arc<PeerGroup>),
+    /// Mount and unmount events are received from the master peer group, but not sent back.
+    Slave(Arc<PeerGroup>),
+    /// A private mount that cannot be bind mounted.
+    Unbindable,
+}
+
+/// A group of mount nodes that propagate mount events to each other.
+pub struct PeerGroup {
+    id: u32,
+    members: SpinLock<Vec<Weak<MountNode>>>,
+    slaves: SpinLock<Vec<Weak<MountNode>>>,
+}
+
+impl PeerGroup {
+    fn new() -> Arc<Self> {
+        static NEXT_PEER_GROUP_ID: AtomicU32 = AtomicU32::new(1);
+
+        Arc::new(Self {
+            id: NEXT_PEER_GROUP_ID.fetch_add(1, Ordering::Relaxed),
+            members: SpinLock::new(Vec::new()),
+            slaves: SpinLock::new(Vec::new()),
+        })
+    }
+
+    /// Gets the ID of the peer group, as reported in `/proc/[pid]/mountinfo`.
+    pub fn id(&self) -> u32 {
+        self.id
+    }
+
+    fn members(&self) -> Vec<Arc<MountNode>> {
+        self.members
+            .lock()
+            .iter()
+            .filter_map(|member| member.upgrade())
+            .collect()
+    }
+
+    fn slaves(&self) -> Vec<Arc<MountNode>> {
+        self.slaves
+            .lock()
+            .iter()
+            .filter_map(|slave| slave.upgrade())
+            .collect()
+    }
+
+    fn remove_member(&self, mount_node: &MountNode) {
+        self.members
+            .lock()
+            .retain(|member| !core::ptr::eq(member.as_ptr(), mount_node));
+    }
+
+    fn remove_slave(&self, mount_node: &MountNode) {
+        self.slaves
+            .lock()
+            .retain(|slave| !core::ptr::eq(slave.as_ptr(), mount_node));
+    }
+}
+
+impl Debug for PeerGroup {
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        f.debug_struct("PeerGroup").field("id", &self.id).finish()
+    }
+}
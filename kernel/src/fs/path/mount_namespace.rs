@@ -0,0 +1,103 @@
+// SPDX-License-Identifier: MPL-2.0
+
+use hashbrown::HashMap;
+use spin::Once;
+
+use crate::{
+    fs::{
+        fs_resolver::FsResolver,
+        path::{dentry::Dentry, mount::MountNode},
+        rootfs::root_mount,
+    },
+    prelude::*,
+};
+
+/// A mount namespace.
+///
+/// A mount namespace owns a mount tree, which determines the view of the file
+/// system hierarchy for the threads in the namespace. Mount and unmount events
+/// in one namespace are invisible to other namespaces, unless they are propagated
+/// through shared mounts.
+pub struct MountNamespace {
+    root: Arc<MountNode>,
+}
+
+impl MountNamespace {
+    /// Gets the initial mount namespace, whose root is the root mount of the rootfs.
+    pub fn get_init_singleton() -> &'static Arc<MountNamespace> {
+        static INIT: Once<Arc<MountNamespace>> = Once::new();
+
+        INIT.call_once(|| {
+            Arc::new(Self {
+                root: root_mount().clone(),
+            })
+        })
+    }
+
+    /// Gets the root mount node of the namespace.
+    pub fn root(&self) -> &Arc<MountNode> {
+        &self.root
+    }
+
+    /// Creates a new mount namespace by copying the mount tree of this namespace.
+    ///
+    /// The `resolver` is updated to refer to the copied mount tree, so its root and
+    /// current working directory stay at the same locations in the new namespace.
+    /// The copied mount nodes keep the propagation type of the original ones, which
+    /// means that shared mounts remain in the same peer groups across namespaces.
+    pub fn copy(&self, resolver: &mut FsResolver) -> Arc<Self> {
+        let mut node_map = HashMap::new();
+        let new_root = self.root.copy_mount_node_tree(|old_node, new_node| {
+            node_map.insert(Arc::as_ptr(old_node) as usize, new_node.clone());
+        });
+
+        let translate = |dentry: &Dentry| -> Option<Dentry> {
+            let old_node_ptr = Arc::as_ptr(dentry.mount_node()) as usize;
+            let new_node = node_map.get(&old_node_ptr)?;
+            Some(Dentry::new(new_node.clone(), dentry.inner().clone()))
+        };
+        if let Some(new_root_dentry) = translate(resolver.root()) {
+            resolver.set_root(new_root_dentry);
+        }
+        if let Some(new_cwd_dentry) = translate(resolver.cwd()) {
+            resolver.set_cwd(new_cwd_dentry);
+        }
+
+        Arc::new(Self { root: new_root })
+    }
+
+    /// Collects all the mount nodes in the namespace.
+    ///
+    /// The parent mount nodes always appear before their children.
+    pub fn mounts(&self) -> Vec<Arc<MountNode>> {
+        let mut mounts = Vec::new();
+        let mut stack = vec![self.root.clone()];
+        while let Some(mount_node) = stack.pop() {
+            stack.extend(mount_node.children());
+            mounts.push(mount_node);
+        }
+        mounts
+    }
+
+    /// Checks whether the mount node belongs to this namespace.
+    pub fn owns(&self, mount_node: &Arc<MountNode>) -> bool {
+        let mut current = mount_node.clone();
+        loop {
+            if Arc::ptr_eq(&current, &self.root) {
+                return true;
+            }
+            let Some(parent) = current.parent().and_then(|parent| parent.upgrade()) else {
+                return false;
+            };
+            current = parent;
+        }
+    }
+}
+
+impl Debug for MountNamespace {
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        f.debug_struct("MountNamespace")
+            .field("root", &self.root)
+            .finish()
+    }
+}
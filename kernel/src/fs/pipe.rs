@@ -18,12 +18,24 @@ use crate::{
 
 const DEFAULT_PIPE_BUF_SIZE: usize = 65536;
 
+/// The maximum capacity of a pipe that an unprivileged user can set.
+///
+/// This is the default value of `/proc/sys/fs/pipe-max-size` on Linux.
+pub const PIPE_MAX_SIZE: usize = 1024 * 1024;
+
 pub fn new_pair() -> Result<(Arc<PipeReader>, Arc<PipeWriter>)> {
+    new_pair_with_flags(StatusFlags::empty())
+}
+
+/// Creates a pair of pipe ends, both of which have the given status flags.
+pub fn new_pair_with_flags(
+    status_flags: StatusFlags,
+) -> Result<(Arc<PipeReader>, Arc<PipeWriter>)> {
     let (producer, consumer) = Channel::with_capacity(DEFAULT_PIPE_BUF_SIZE).split();
 
     Ok((
-        PipeReader::new(consumer, StatusFlags::empty())?,
-        PipeWriter::new(producer, StatusFlags::empty())?,
+        PipeReader::new(consumer, status_flags)?,
+        PipeWriter::new(producer, status_flags)?,
     ))
 }
 
@@ -50,6 +62,23 @@ impl PipeReader {
             status_flags: AtomicU32::new(status_flags.bits()),
         }))
     }
+
+    /// Reads data from the pipe without blocking, regardless of the status flags.
+    pub fn try_read(&self, writer: &mut VmWriter) -> Result<usize> {
+        self.consumer.try_read(writer)
+    }
+
+    /// Returns the capacity of the pipe.
+    pub fn capacity(&self) -> usize {
+        self.consumer.capacity()
+    }
+
+    /// Sets the capacity of the pipe.
+    ///
+    /// The new capacity must be a power of two.
+    pub fn set_capacity(&self, new_capacity: usize) -> Result<()> {
+        self.consumer.set_capacity(new_capacity)
+    }
 }
 
 impl Pollable for PipeReader {
@@ -120,6 +149,38 @@ impl PipeWriter {
             status_flags: AtomicU32::new(status_flags.bits()),
         }))
     }
+
+    /// Writes data to the pipe without blocking, regardless of the status flags.
+    ///
+    /// If the pipe is in packet mode (i.e., `O_DIRECT` is set), the data is written as
+    /// packets of at most `PIPE_BUF` bytes.
+    pub fn try_write(&self, reader: &mut VmReader) -> Result<usize> {
+        if !self.status_flags().contains(StatusFlags::O_DIRECT) {
+            return self.producer.try_write(reader);
+        }
+
+        let mut written_len = 0;
+        while reader.has_remain() {
+            match self.producer.try_write_packet(reader) {
+                Ok(len) => written_len += len,
+                Err(_) if written_len > 0 => break,
+                Err(err) => return Err(err),
+            }
+        }
+        Ok(written_len)
+    }
+
+    /// Returns the capacity of the pipe.
+    pub fn capacity(&self) -> usize {
+        self.producer.capacity()
+    }
+
+    /// Sets the capacity of the pipe.
+    ///
+    /// The new capacity must be a power of two.
+    pub fn set_capacity(&self, new_capacity: usize) -> Result<()> {
+        self.producer.set_capacity(new_capacity)
+    }
 }
 
 impl Pollable for PipeWriter {
@@ -131,9 +192,9 @@ impl Pollable for PipeWriter {
 impl FileLike for PipeWriter {
     fn write(&self, reader: &mut VmReader) -> Result<usize> {
         if self.status_flags().contains(StatusFlags::O_NONBLOCK) {
-            self.producer.try_write(reader)
+            self.try_write(reader)
         } else {
-            self.wait_events(IoEvents::OUT, None, || self.producer.try_write(reader))
+            self.wait_events(IoEvents::OUT, None, || self.try_write(reader))
         }
     }
 
@@ -175,15 +236,10 @@ impl FileLike for PipeWriter {
     }
 }
 
-fn check_status_flags(status_flags: StatusFlags) -> Result<()> {
-    if status_flags.contains(StatusFlags::O_DIRECT) {
-        // "O_DIRECT .. Older kernels that do not support this flag will indicate this via an
-        // EINVAL error."
-        //
-        // See <https://man7.org/linux/man-pages/man2/pipe.2.html>.
-        return_errno_with_message!(Errno::EINVAL, "the `O_DIRECT` flag is not supported");
-    }
-
+fn check_status_flags(_status_flags: StatusFlags) -> Result<()> {
+    // The `O_DIRECT` flag enables the packet mode. For more details, see the description of
+    // `O_DIRECT` in <https://man7.org/linux/man-pages/man2/pipe.2.html>.
+    //
     // TODO: Setting most of the other flags will succeed on Linux, but their effects need to be
     // validated.
 
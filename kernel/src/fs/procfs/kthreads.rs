@@ -0,0 +1,50 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! This module offers `/proc/kthreads` file support, which tells the user space
+//! about the names and the states of the kernel threads.
+//!
+//! Each line describes a kernel thread, with its name and its state. The state
+//! is `P` if the kernel thread is parked, `T` if it is stopped, and `R`
+//! otherwise.
+//!
+//! Linux lists the kernel threads as processes instead. This file is specific to
+//! Asterinas, since the kernel threads do not have PIDs here.
+
+use alloc::format;
+
+use crate::{
+    fs::{
+        procfs::template::{FileOps, ProcFileBuilder},
+        utils::Inode,
+    },
+    prelude::*,
+    thread::kernel_thread::{all_kernel_threads, AsKernelThread},
+};
+
+/// Represents the inode at `/proc/kthreads`.
+pub struct KthreadsFileOps;
+
+impl KthreadsFileOps {
+    pub fn new_inode(parent: Weak<dyn Inode>) -> Arc<dyn Inode> {
+        ProcFileBuilder::new(Self).parent(parent).build().unwrap()
+    }
+}
+
+impl FileOps for KthreadsFileOps {
+    fn data(&self) -> Result<Vec<u8>> {
+        let mut output = String::new();
+        for thread in all_kernel_threads() {
+            let kernel_thread = thread.as_kernel_thread().unwrap();
+            let state = if kernel_thread.is_parked() {
+                'P'
+            } else if thread.is_stopped() {
+                'T'
+            } else {
+                'R'
+            };
+            output.push_str(&format!("{}\t{}\n", kernel_thread.name(), state));
+        }
+
+        Ok(output.into_bytes())
+    }
+}
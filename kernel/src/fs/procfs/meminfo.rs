@@ -16,6 +16,7 @@ use crate::{
         utils::Inode,
     },
     prelude::*,
+    vm::swap::swap_areas,
 };
 
 /// Represents the inode at `/proc/meminfo`.
@@ -38,11 +39,30 @@ fn mem_available() -> usize {
     stat::mem_available()
 }
 
+/// Total swap space in the entire system in bytes.
+fn swap_total() -> usize {
+    swap_areas().iter().map(|area| area.size()).sum()
+}
+
+/// Unused swap space in the entire system in bytes.
+fn swap_free() -> usize {
+    swap_areas()
+        .iter()
+        .map(|area| area.size() - area.used())
+        .sum()
+}
+
 impl FileOps for MemInfoFileOps {
     fn data(&self) -> Result<Vec<u8>> {
         let total = mem_total();
         let available = mem_available();
-        let output = format!("MemTotal:\t{}\nMemAvailable:\t{}\n", total, available);
+        let output = format!(
+            "MemTotal:\t{}\nMemAvailable:\t{}\nSwapTotal:\t{}\nSwapFree:\t{}\n",
+            total,
+            available,
+            swap_total(),
+            swap_free()
+        );
         Ok(output.into_bytes())
     }
 }
@@ -4,12 +4,16 @@ use core::sync::atomic::{AtomicU64, Ordering};
 
 use filesystems::{FileSystemType, FILESYSTEM_TYPES};
 
+pub use self::pid::namespace_of;
 use self::{
     cpuinfo::CpuInfoFileOps,
+    kthreads::KthreadsFileOps,
     loadavg::LoadAvgFileOps,
     meminfo::MemInfoFileOps,
+    net::NetDirOps,
     pid::PidDirOps,
     self_::SelfSymOps,
+    swaps::SwapsFileOps,
     sys::SysDirOps,
     template::{DirOps, ProcDir, ProcDirBuilder, ProcSymBuilder, SymOps},
     thread_self::ThreadSelfSymOps,
@@ -29,10 +33,13 @@ use crate::{
 
 mod cpuinfo;
 mod filesystems;
+mod kthreads;
 mod loadavg;
 mod meminfo;
+mod net;
 mod pid;
 mod self_;
+mod swaps;
 mod sys;
 mod template;
 mod thread_self;
@@ -43,6 +50,8 @@ pub(super) fn init() {
             FileSystemType::new("proc", true),
             FileSystemType::new("ramfs", true),
             FileSystemType::new("devpts", true),
+            FileSystemType::new("cgroup2", true),
+            FileSystemType::new("binfmt_misc", true),
             FileSystemType::new("ext2", false),
             FileSystemType::new("exfat", false),
         ]
@@ -92,6 +101,10 @@ impl FileSystem for ProcFS {
     fn flags(&self) -> FsFlags {
         FsFlags::empty()
     }
+
+    fn name(&self) -> &'static str {
+        "proc"
+    }
 }
 
 /// Represents the inode at `/proc`.
@@ -124,6 +137,8 @@ impl DirOps for RootDirOps {
             SelfSymOps::new_inode(this_ptr.clone())
         } else if name == "sys" {
             SysDirOps::new_inode(this_ptr.clone())
+        } else if name == "net" {
+            NetDirOps::new_inode(this_ptr.clone())
         } else if name == "thread-self" {
             ThreadSelfSymOps::new_inode(this_ptr.clone())
         } else if name == "filesystems" {
@@ -134,6 +149,10 @@ impl DirOps for RootDirOps {
             LoadAvgFileOps::new_inode(this_ptr.clone())
         } else if name == "cpuinfo" {
             CpuInfoFileOps::new_inode(this_ptr.clone())
+        } else if name == "swaps" {
+            SwapsFileOps::new_inode(this_ptr.clone())
+        } else if name == "kthreads" {
+            KthreadsFileOps::new_inode(this_ptr.clone())
         } else if let Ok(pid) = name.parse::<Pid>() {
             let process_ref =
                 process_table::get_process(pid).ok_or_else(|| Error::new(Errno::ENOENT))?;
@@ -155,6 +174,7 @@ impl DirOps for RootDirOps {
             ThreadSelfSymOps::new_inode(this_ptr.clone())
         });
         cached_children.put_entry_if_not_found("sys", || SysDirOps::new_inode(this_ptr.clone()));
+        cached_children.put_entry_if_not_found("net", || NetDirOps::new_inode(this_ptr.clone()));
         cached_children.put_entry_if_not_found("filesystems", || {
             FileSystemsFileOps::new_inode(this_ptr.clone())
         });
@@ -164,6 +184,10 @@ impl DirOps for RootDirOps {
             .put_entry_if_not_found("loadavg", || LoadAvgFileOps::new_inode(this_ptr.clone()));
         cached_children
             .put_entry_if_not_found("cpuinfo", || CpuInfoFileOps::new_inode(this_ptr.clone()));
+        cached_children
+            .put_entry_if_not_found("swaps", || SwapsFileOps::new_inode(this_ptr.clone()));
+        cached_children
+            .put_entry_if_not_found("kthreads", || KthreadsFileOps::new_inode(this_ptr.clone()));
         for process in process_table::process_table_mut().iter() {
             let pid = process.pid().to_string();
             cached_children.put_entry_if_not_found(&pid, || {
@@ -0,0 +1,68 @@
+// SPDX-License-Identifier: MPL-2.0
+
+use core::fmt::Write;
+
+use aster_bigtcp::wire::{IpAddress, IpEndpoint};
+
+use self::{pnp::PnpFileOps, snmp::SnmpFileOps, tcp::TcpFileOps, udp::UdpFileOps};
+use crate::{
+    fs::{
+        procfs::template::{DirOps, ProcDir, ProcDirBuilder},
+        utils::{DirEntryVecExt, Inode},
+    },
+    prelude::*,
+};
+
+mod pnp;
+mod snmp;
+mod tcp;
+mod udp;
+
+/// Represents the inode at `/proc/net`.
+pub struct NetDirOps;
+
+impl NetDirOps {
+    pub fn new_inode(parent: Weak<dyn Inode>) -> Arc<dyn Inode> {
+        ProcDirBuilder::new(Self).parent(parent).build().unwrap()
+    }
+}
+
+impl DirOps for NetDirOps {
+    fn lookup_child(&self, this_ptr: Weak<dyn Inode>, name: &str) -> Result<Arc<dyn Inode>> {
+        let inode = match name {
+            "pnp" => PnpFileOps::new_inode(this_ptr.clone()),
+            "snmp" => SnmpFileOps::new_inode(this_ptr.clone()),
+            "tcp" => TcpFileOps::new_inode(this_ptr.clone()),
+            "udp" => UdpFileOps::new_inode(this_ptr.clone()),
+            _ => return_errno!(Errno::ENOENT),
+        };
+        Ok(inode)
+    }
+
+    fn populate_children(&self, this_ptr: Weak<dyn Inode>) {
+        let this = {
+            let this = this_ptr.upgrade().unwrap();
+            this.downcast_ref::<ProcDir<NetDirOps>>().unwrap().this()
+        };
+        let mut cached_children = this.cached_children().write();
+        cached_children.put_entry_if_not_found("pnp", || PnpFileOps::new_inode(this_ptr.clone()));
+        cached_children.put_entry_if_not_found("snmp", || SnmpFileOps::new_inode(this_ptr.clone()));
+        cached_children.put_entry_if_not_found("tcp", || TcpFileOps::new_inode(this_ptr.clone()));
+        cached_children.put_entry_if_not_found("udp", || UdpFileOps::new_inode(this_ptr.clone()));
+    }
+}
+
+/// Writes an IPv4 endpoint in the format of `/proc/net/tcp` and `/proc/net/udp`.
+///
+/// The address is written as the hexadecimal number of its bytes in the native byte order, which
+/// is how Linux writes it. `None` is written as the unspecified endpoint.
+fn write_ipv4_endpoint(output: &mut String, endpoint: Option<IpEndpoint>) {
+    let (addr, port) = match endpoint {
+        Some(IpEndpoint {
+            addr: IpAddress::Ipv4(addr),
+            port,
+        }) => (u32::from_ne_bytes(addr.octets()), port),
+        _ => (0, 0),
+    };
+    write!(output, "{:08X}:{:04X}", addr, port).unwrap();
+}
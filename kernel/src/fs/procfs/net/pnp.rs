@@ -0,0 +1,45 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! This module offers `/proc/net/pnp` file support, which tells the user space about the DNS
+//! servers obtained by the in-kernel DHCP client, in a format compatible with `resolv.conf`.
+//!
+//! Reference: <https://www.kernel.org/doc/html/v6.4/admin-guide/nfs/nfsroot.html>
+
+use core::fmt::Write;
+
+use crate::{
+    fs::{
+        procfs::template::{FileOps, ProcFileBuilder},
+        utils::Inode,
+    },
+    net::iface::iter_ifaces_with_index,
+    prelude::*,
+};
+
+/// Represents the inode at `/proc/net/pnp`.
+pub struct PnpFileOps;
+
+impl PnpFileOps {
+    pub fn new_inode(parent: Weak<dyn Inode>) -> Arc<dyn Inode> {
+        ProcFileBuilder::new(Self).parent(parent).build().unwrap()
+    }
+}
+
+impl FileOps for PnpFileOps {
+    fn data(&self) -> Result<Vec<u8>> {
+        let lease = iter_ifaces_with_index().find_map(|(_, iface)| iface.dhcp_lease());
+
+        let Some(lease) = lease else {
+            // Similar to Linux, this means that the network is configured manually.
+            return Ok(b"#MANUAL\n".to_vec());
+        };
+
+        let mut output = String::from("#PROTO: DHCP\n");
+        for dns_server in lease.dns_servers.iter() {
+            writeln!(output, "nameserver {}", dns_server).unwrap();
+        }
+        writeln!(output, "bootserver {}", lease.server_addr).unwrap();
+
+        Ok(output.into_bytes())
+    }
+}
@@ -0,0 +1,124 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! This module offers `/proc/net/snmp` file support, which tells the user space about the SNMP
+//! counters of the network stack, in a format compatible with Linux.
+//!
+//! The counters that are not tracked are always reported as zero.
+
+use core::fmt::Write;
+
+use aster_bigtcp::{iface::SnmpCounter, socket::TcpState};
+
+use crate::{
+    fs::{
+        procfs::template::{FileOps, ProcFileBuilder},
+        utils::Inode,
+    },
+    net::iface::iter_ifaces_with_index,
+    prelude::*,
+};
+
+/// Represents the inode at `/proc/net/snmp`.
+pub struct SnmpFileOps;
+
+impl SnmpFileOps {
+    pub fn new_inode(parent: Weak<dyn Inode>) -> Arc<dyn Inode> {
+        ProcFileBuilder::new(Self).parent(parent).build().unwrap()
+    }
+}
+
+impl FileOps for SnmpFileOps {
+    fn data(&self) -> Result<Vec<u8>> {
+        let counter = |counter| -> i64 {
+            iter_ifaces_with_index()
+                .map(|(_, iface)| iface.snmp_counter(counter))
+                .sum::<u64>() as i64
+        };
+        let curr_estab = iter_ifaces_with_index()
+            .flat_map(|(_, iface)| iface.tcp_socket_infos())
+            .filter(|info| matches!(info.state, TcpState::Established | TcpState::CloseWait))
+            .count() as i64;
+
+        let mut output = String::new();
+
+        write_line(
+            &mut output,
+            "Ip",
+            &[
+                // 2 means that IP forwarding is disabled.
+                ("Forwarding", 2),
+                ("DefaultTTL", 64),
+                ("InReceives", counter(SnmpCounter::IpInReceives)),
+                ("InHdrErrors", 0),
+                ("InAddrErrors", 0),
+                ("ForwDatagrams", 0),
+                ("InUnknownProtos", 0),
+                ("InDiscards", 0),
+                ("InDelivers", counter(SnmpCounter::IpInDelivers)),
+                ("OutRequests", counter(SnmpCounter::IpOutRequests)),
+                ("OutDiscards", 0),
+                ("OutNoRoutes", 0),
+                ("ReasmTimeout", 0),
+                ("ReasmReqds", 0),
+                ("ReasmOKs", 0),
+                ("ReasmFails", 0),
+                ("FragOKs", 0),
+                ("FragCreates", 0),
+            ],
+        );
+        write_line(
+            &mut output,
+            "Tcp",
+            &[
+                // The values are the same as Linux.
+                ("RtoAlgorithm", 1),
+                ("RtoMin", 200),
+                ("RtoMax", 120000),
+                ("MaxConn", -1),
+                ("ActiveOpens", counter(SnmpCounter::TcpActiveOpens)),
+                ("PassiveOpens", counter(SnmpCounter::TcpPassiveOpens)),
+                ("AttemptFails", 0),
+                ("EstabResets", 0),
+                ("CurrEstab", curr_estab),
+                ("InSegs", counter(SnmpCounter::TcpInSegs)),
+                ("OutSegs", counter(SnmpCounter::TcpOutSegs)),
+                ("RetransSegs", 0),
+                ("InErrs", 0),
+                ("OutRsts", counter(SnmpCounter::TcpOutRsts)),
+                ("InCsumErrors", 0),
+            ],
+        );
+        write_line(
+            &mut output,
+            "Udp",
+            &[
+                ("InDatagrams", counter(SnmpCounter::UdpInDatagrams)),
+                ("NoPorts", counter(SnmpCounter::UdpNoPorts)),
+                ("InErrors", 0),
+                ("OutDatagrams", counter(SnmpCounter::UdpOutDatagrams)),
+                ("RcvbufErrors", 0),
+                ("SndbufErrors", 0),
+                ("InCsumErrors", 0),
+                ("IgnoredMulti", 0),
+                ("MemErrors", 0),
+            ],
+        );
+
+        Ok(output.into_bytes())
+    }
+}
+
+/// Writes a line of the field names followed by a line of the values.
+fn write_line(output: &mut String, protocol: &str, fields: &[(&str, i64)]) {
+    write!(output, "{}:", protocol).unwrap();
+    for (name, _) in fields.iter() {
+        write!(output, " {}", name).unwrap();
+    }
+    output.push('\n');
+
+    write!(output, "{}:", protocol).unwrap();
+    for (_, value) in fields.iter() {
+        write!(output, " {}", value).unwrap();
+    }
+    output.push('\n');
+}
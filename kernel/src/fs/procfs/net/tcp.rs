@@ -0,0 +1,80 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! This module offers `/proc/net/tcp` file support, which tells the user space about the TCP
+//! sockets, in a format compatible with Linux.
+//!
+//! Reference: <https://www.kernel.org/doc/html/v6.4/networking/proc_net_tcp.html>
+
+use core::fmt::Write;
+
+use aster_bigtcp::{socket::TcpState, wire::IpVersion};
+
+use super::write_ipv4_endpoint;
+use crate::{
+    fs::{
+        procfs::template::{FileOps, ProcFileBuilder},
+        utils::Inode,
+    },
+    net::iface::iter_ifaces_with_index,
+    prelude::*,
+};
+
+/// Represents the inode at `/proc/net/tcp`.
+pub struct TcpFileOps;
+
+impl TcpFileOps {
+    pub fn new_inode(parent: Weak<dyn Inode>) -> Arc<dyn Inode> {
+        ProcFileBuilder::new(Self).parent(parent).build().unwrap()
+    }
+}
+
+impl FileOps for TcpFileOps {
+    fn data(&self) -> Result<Vec<u8>> {
+        let mut output = String::from(
+            "  sl  local_address rem_address   st tx_queue rx_queue tr tm->when retrnsmt   uid  timeout inode\n",
+        );
+
+        // TODO: Report the sockets with IPv6 addresses in `/proc/net/tcp6`.
+        let infos = iter_ifaces_with_index()
+            .flat_map(|(_, iface)| iface.tcp_socket_infos())
+            .filter(|info| info.local_endpoint.addr.version() == IpVersion::Ipv4);
+
+        for (slot, info) in infos.enumerate() {
+            write!(output, "{:4}: ", slot).unwrap();
+            write_ipv4_endpoint(&mut output, Some(info.local_endpoint));
+            output.push(' ');
+            write_ipv4_endpoint(&mut output, info.remote_endpoint);
+            // No timers are reported, and the UID and the inode number are not tracked.
+            writeln!(
+                output,
+                " {:02X} {:08X}:{:08X} 00:00000000 00000000 {:5} {:8} {} 1 0000000000000000 100 0 0 10 0",
+                state_number(info.state),
+                info.send_queue,
+                info.recv_queue,
+                0,
+                0,
+                0,
+            )
+            .unwrap();
+        }
+
+        Ok(output.into_bytes())
+    }
+}
+
+/// Returns the number that Linux uses to represent the TCP state.
+fn state_number(state: TcpState) -> u8 {
+    match state {
+        TcpState::Established => 1,
+        TcpState::SynSent => 2,
+        TcpState::SynReceived => 3,
+        TcpState::FinWait1 => 4,
+        TcpState::FinWait2 => 5,
+        TcpState::TimeWait => 6,
+        TcpState::Closed => 7,
+        TcpState::CloseWait => 8,
+        TcpState::LastAck => 9,
+        TcpState::Listen => 10,
+        TcpState::Closing => 11,
+    }
+}
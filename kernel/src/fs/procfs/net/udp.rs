@@ -0,0 +1,60 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! This module offers `/proc/net/udp` file support, which tells the user space about the UDP
+//! sockets, in a format compatible with Linux.
+
+use core::fmt::Write;
+
+use aster_bigtcp::wire::IpVersion;
+
+use super::write_ipv4_endpoint;
+use crate::{
+    fs::{
+        procfs::template::{FileOps, ProcFileBuilder},
+        utils::Inode,
+    },
+    net::iface::iter_ifaces_with_index,
+    prelude::*,
+};
+
+/// Represents the inode at `/proc/net/udp`.
+pub struct UdpFileOps;
+
+impl UdpFileOps {
+    pub fn new_inode(parent: Weak<dyn Inode>) -> Arc<dyn Inode> {
+        ProcFileBuilder::new(Self).parent(parent).build().unwrap()
+    }
+}
+
+/// The state that Linux reports for the UDP sockets that are not connected.
+const UDP_STATE_CLOSE: u8 = 7;
+
+impl FileOps for UdpFileOps {
+    fn data(&self) -> Result<Vec<u8>> {
+        let mut output = String::from(
+            "   sl  local_address rem_address   st tx_queue rx_queue tr tm->when retrnsmt   uid  timeout inode ref pointer drops\n",
+        );
+
+        // TODO: Report the sockets with IPv6 addresses in `/proc/net/udp6`.
+        let infos = iter_ifaces_with_index()
+            .flat_map(|(_, iface)| iface.udp_socket_infos())
+            .filter(|info| info.local_endpoint.addr.version() == IpVersion::Ipv4);
+
+        // The UDP sockets in the protocol stack are never connected. The remote endpoints of the
+        // connected sockets are only recorded by the sockets in the kernel.
+        for (slot, info) in infos.enumerate() {
+            write!(output, "{:5}: ", slot).unwrap();
+            write_ipv4_endpoint(&mut output, Some(info.local_endpoint));
+            output.push(' ');
+            write_ipv4_endpoint(&mut output, None);
+            writeln!(
+                output,
+                " {:02X} {:08X}:{:08X} 00:00000000 00000000 {:5} {:8} {} 2 0000000000000000 0",
+                UDP_STATE_CLOSE, info.send_queue, info.recv_queue, 0, 0, 0,
+            )
+            .unwrap();
+        }
+
+        Ok(output.into_bytes())
+    }
+}
@@ -0,0 +1,86 @@
+// SPDX-License-Identifier: MPL-2.0
+
+use core::fmt::Write;
+
+use crate::{
+    fs::{
+        procfs::template::{FileOps, ProcFileBuilder},
+        utils::Inode,
+    },
+    prelude::*,
+    process::{
+        rlimit::{RLIMIT_COUNT, RLIM_INFINITY},
+        ResourceType,
+    },
+    Process,
+};
+
+/// Represents the inode at `/proc/[pid]/limits`.
+pub struct LimitsFileOps(Arc<Process>);
+
+impl LimitsFileOps {
+    pub fn new_inode(process_ref: Arc<Process>, parent: Weak<dyn Inode>) -> Arc<dyn Inode> {
+        ProcFileBuilder::new(Self(process_ref))
+            .parent(parent)
+            .build()
+            .unwrap()
+    }
+}
+
+/// The names and the units of the resource limits, in the order of [`ResourceType`].
+///
+/// Reference: <https://elixir.bootlin.com/linux/v6.13/source/fs/proc/base.c>
+const LIMIT_NAMES: [(&str, Option<&str>); RLIMIT_COUNT] = [
+    ("Max cpu time", Some("seconds")),
+    ("Max file size", Some("bytes")),
+    ("Max data size", Some("bytes")),
+    ("Max stack size", Some("bytes")),
+    ("Max core file size", Some("bytes")),
+    ("Max resident set", Some("bytes")),
+    ("Max processes", Some("processes")),
+    ("Max open files", Some("files")),
+    ("Max locked memory", Some("bytes")),
+    ("Max address space", Some("bytes")),
+    ("Max file locks", Some("locks")),
+    ("Max pending signals", Some("signals")),
+    ("Max msgqueue size", Some("bytes")),
+    ("Max nice priority", None),
+    ("Max realtime priority", None),
+    ("Max realtime timeout", Some("us")),
+];
+
+impl FileOps for LimitsFileOps {
+    fn data(&self) -> Result<Vec<u8>> {
+        let format_limit = |limit: u64| {
+            if limit == RLIM_INFINITY {
+                "unlimited".to_string()
+            } else {
+                limit.to_string()
+            }
+        };
+
+        let mut output = format!(
+            "{:<25} {:<20} {:<20} {:<10}\n",
+            "Limit", "Soft Limit", "Hard Limit", "Units"
+        );
+        let resource_limits = self.0.resource_limits().lock();
+        for (resource, (name, unit)) in LIMIT_NAMES.iter().enumerate() {
+            let resource = ResourceType::try_from(resource as u32).unwrap();
+            let rlimit = resource_limits.get_rlimit(resource);
+            write!(
+                output,
+                "{:<25} {:<20} {:<20} ",
+                name,
+                format_limit(rlimit.get_cur()),
+                format_limit(rlimit.get_max())
+            )
+            .unwrap();
+            if let Some(unit) = unit {
+                write!(output, "{:<10}", unit).unwrap();
+            }
+            output.push('\n');
+        }
+
+        Ok(output.into_bytes())
+    }
+}
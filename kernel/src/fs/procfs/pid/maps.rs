@@ -0,0 +1,140 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! This module offers `/proc/[pid]/maps` and `/proc/[pid]/smaps`, which describe the memory
+//! mappings of a process.
+//!
+//! The pathnames of the mapped files are not shown yet, since the mappings do not record the
+//! files that they are created from.
+//!
+//! Reference: <https://man7.org/linux/man-pages/man5/proc_pid_smaps.5.html>
+
+use alloc::format;
+use core::fmt::{self, Write};
+
+use crate::{
+    fs::{
+        procfs::template::{FileOps, ProcFileBuilder},
+        utils::Inode,
+    },
+    prelude::*,
+    vm::{perms::VmPerms, thp::HugePageAdvice, vmar::MappingStat},
+    Process,
+};
+
+/// Represents the inode at `/proc/[pid]/maps`.
+pub struct MapsFileOps(Arc<Process>);
+
+impl MapsFileOps {
+    pub fn new_inode(process_ref: Arc<Process>, parent: Weak<dyn Inode>) -> Arc<dyn Inode> {
+        ProcFileBuilder::new(Self(process_ref))
+            .parent(parent)
+            .build()
+            .unwrap()
+    }
+}
+
+impl FileOps for MapsFileOps {
+    fn data(&self) -> Result<Vec<u8>> {
+        let mut output = String::new();
+        for stat in self.0.root_vmar().mapping_stats() {
+            write_header(&mut output, &stat);
+        }
+        Ok(output.into_bytes())
+    }
+}
+
+/// Represents the inode at `/proc/[pid]/smaps`.
+pub struct SmapsFileOps(Arc<Process>);
+
+impl SmapsFileOps {
+    pub fn new_inode(process_ref: Arc<Process>, parent: Weak<dyn Inode>) -> Arc<dyn Inode> {
+        ProcFileBuilder::new(Self(process_ref))
+            .parent(parent)
+            .build()
+            .unwrap()
+    }
+}
+
+impl FileOps for SmapsFileOps {
+    fn data(&self) -> Result<Vec<u8>> {
+        let mut output = String::new();
+        for stat in self.0.root_vmar().mapping_stats() {
+            write_header(&mut output, &stat);
+
+            let locked = if stat.is_locked { stat.pss } else { 0 };
+            let fields = [
+                ("Size:", stat.range.len()),
+                ("KernelPageSize:", PAGE_SIZE),
+                ("MMUPageSize:", PAGE_SIZE),
+                ("Rss:", stat.rss),
+                ("Pss:", stat.pss),
+                ("Shared_Clean:", stat.shared_clean),
+                ("Shared_Dirty:", stat.shared_dirty),
+                ("Private_Clean:", stat.private_clean),
+                ("Private_Dirty:", stat.private_dirty),
+                ("Referenced:", stat.referenced),
+                ("Anonymous:", stat.anonymous),
+                ("Swap:", stat.swap),
+                ("SwapPss:", stat.swap_pss),
+                ("Locked:", locked),
+            ];
+            for (name, size) in fields {
+                writeln!(output, "{:<16}{:>8} kB", name, size / 1024).unwrap();
+            }
+
+            writeln!(output, "VmFlags: {}", VmFlags(&stat)).unwrap();
+        }
+        Ok(output.into_bytes())
+    }
+}
+
+/// Writes the line that describes the mapping in `/proc/[pid]/maps`.
+fn write_header(output: &mut String, stat: &MappingStat) {
+    let perm = |perm: VmPerms, c: char| if stat.perms.contains(perm) { c } else { '-' };
+    let header = format!(
+        "{:08x}-{:08x} {}{}{}{} {:08x} 00:00 0",
+        stat.range.start,
+        stat.range.end,
+        perm(VmPerms::READ, 'r'),
+        perm(VmPerms::WRITE, 'w'),
+        perm(VmPerms::EXEC, 'x'),
+        if stat.is_shared { 's' } else { 'p' },
+        stat.vmo_offset.unwrap_or(0),
+    );
+
+    if stat.grows_down {
+        // Like Linux, the pathname is padded to start at the 74th column.
+        writeln!(output, "{:<73}[stack]", header).unwrap();
+    } else {
+        writeln!(output, "{}", header).unwrap();
+    }
+}
+
+/// Formats the flags of a mapping as the two-letter codes used by Linux.
+struct VmFlags<'a>(&'a MappingStat);
+
+impl fmt::Display for VmFlags<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let stat = self.0;
+        let flags = [
+            (stat.perms.contains(VmPerms::READ), "rd"),
+            (stat.perms.contains(VmPerms::WRITE), "wr"),
+            (stat.perms.contains(VmPerms::EXEC), "ex"),
+            (stat.is_shared, "sh"),
+            (true, "mr"),
+            (true, "mw"),
+            (true, "me"),
+            (stat.is_shared, "ms"),
+            (stat.grows_down, "gd"),
+            (stat.is_locked, "lo"),
+            (stat.vmo_offset.is_none() && !stat.is_shared, "ac"),
+            (stat.huge_page_advice == HugePageAdvice::HugePage, "hg"),
+            (stat.huge_page_advice == HugePageAdvice::NoHugePage, "nh"),
+            (stat.is_mergeable, "mg"),
+        ];
+        for (_, code) in flags.iter().filter(|(is_set, _)| *is_set) {
+            write!(f, "{} ", code)?;
+        }
+        Ok(())
+    }
+}
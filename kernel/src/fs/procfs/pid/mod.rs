@@ -1,7 +1,20 @@
 // SPDX-License-Identifier: MPL-2.0
 
+pub use self::ns::namespace_of;
 use self::{
-    cmdline::CmdlineFileOps, comm::CommFileOps, exe::ExeSymOps, fd::FdDirOps, task::TaskDirOps,
+    cgroup::CgroupFileOps,
+    cmdline::CmdlineFileOps,
+    comm::CommFileOps,
+    exe::ExeSymOps,
+    fd::FdDirOps,
+    limits::LimitsFileOps,
+    maps::{MapsFileOps, SmapsFileOps},
+    mounts::{MountInfoFileOps, MountsFileOps},
+    ns::NsDirOps,
+    oom_score::OomScoreFileOps,
+    oom_score_adj::OomScoreAdjFileOps,
+    pagemap::PagemapFileOps,
+    task::TaskDirOps,
 };
 use super::template::{DirOps, ProcDir, ProcDirBuilder};
 use crate::{
@@ -14,10 +27,18 @@ use crate::{
     process::{posix_thread::AsPosixThread, Process},
 };
 
+mod cgroup;
 mod cmdline;
 mod comm;
 mod exe;
 mod fd;
+mod limits;
+mod maps;
+mod mounts;
+mod ns;
+mod oom_score;
+mod oom_score_adj;
+mod pagemap;
 mod stat;
 mod status;
 mod task;
@@ -34,7 +55,8 @@ impl PidDirOps {
             .build()
             .unwrap();
         let main_thread = process_ref.main_thread();
-        let file_table = main_thread.as_posix_thread().unwrap().file_table().read();
+        let file_table = main_thread.as_posix_thread().unwrap().file_table().lock();
+        let file_table = file_table.read();
         let weak_ptr = Arc::downgrade(&pid_inode);
         file_table.register_observer(weak_ptr);
         pid_inode
@@ -60,6 +82,16 @@ impl DirOps for PidDirOps {
             "status" => status::StatusFileOps::new_inode(self.0.clone(), this_ptr.clone()),
             "stat" => stat::StatFileOps::new_inode(self.0.clone(), this_ptr.clone()),
             "task" => TaskDirOps::new_inode(self.0.clone(), this_ptr.clone()),
+            "mounts" => MountsFileOps::new_inode(self.0.clone(), this_ptr.clone()),
+            "mountinfo" => MountInfoFileOps::new_inode(self.0.clone(), this_ptr.clone()),
+            "cgroup" => CgroupFileOps::new_inode(self.0.clone(), this_ptr.clone()),
+            "limits" => LimitsFileOps::new_inode(self.0.clone(), this_ptr.clone()),
+            "ns" => NsDirOps::new_inode(self.0.clone(), this_ptr.clone()),
+            "oom_score" => OomScoreFileOps::new_inode(self.0.clone(), this_ptr.clone()),
+            "oom_score_adj" => OomScoreAdjFileOps::new_inode(self.0.clone(), this_ptr.clone()),
+            "maps" => MapsFileOps::new_inode(self.0.clone(), this_ptr.clone()),
+            "smaps" => SmapsFileOps::new_inode(self.0.clone(), this_ptr.clone()),
+            "pagemap" => PagemapFileOps::new_inode(self.0.clone(), this_ptr.clone()),
             _ => return_errno!(Errno::ENOENT),
         };
         Ok(inode)
@@ -92,5 +124,35 @@ impl DirOps for PidDirOps {
         cached_children.put_entry_if_not_found("task", || {
             TaskDirOps::new_inode(self.0.clone(), this_ptr.clone())
         });
+        cached_children.put_entry_if_not_found("mounts", || {
+            MountsFileOps::new_inode(self.0.clone(), this_ptr.clone())
+        });
+        cached_children.put_entry_if_not_found("mountinfo", || {
+            MountInfoFileOps::new_inode(self.0.clone(), this_ptr.clone())
+        });
+        cached_children.put_entry_if_not_found("cgroup", || {
+            CgroupFileOps::new_inode(self.0.clone(), this_ptr.clone())
+        });
+        cached_children.put_entry_if_not_found("limits", || {
+            LimitsFileOps::new_inode(self.0.clone(), this_ptr.clone())
+        });
+        cached_children.put_entry_if_not_found("ns", || {
+            NsDirOps::new_inode(self.0.clone(), this_ptr.clone())
+        });
+        cached_children.put_entry_if_not_found("oom_score", || {
+            OomScoreFileOps::new_inode(self.0.clone(), this_ptr.clone())
+        });
+        cached_children.put_entry_if_not_found("oom_score_adj", || {
+            OomScoreAdjFileOps::new_inode(self.0.clone(), this_ptr.clone())
+        });
+        cached_children.put_entry_if_not_found("maps", || {
+            MapsFileOps::new_inode(self.0.clone(), this_ptr.clone())
+        });
+        cached_children.put_entry_if_not_found("smaps", || {
+            SmapsFileOps::new_inode(self.0.clone(), this_ptr.clone())
+        });
+        cached_children.put_entry_if_not_found("pagemap", || {
+            PagemapFileOps::new_inode(self.0.clone(), this_ptr.clone())
+        });
     }
 }
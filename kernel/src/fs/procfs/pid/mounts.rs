@@ -0,0 +1,105 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! This module offers `/proc/[pid]/mounts` and `/proc/[pid]/mountinfo` file support,
+//! which list the mounts in the mount namespace of the process.
+//!
+//! Reference: <https://man7.org/linux/man-pages/man5/proc_pid_mountinfo.5.html>
+
+use alloc::format;
+use core::fmt::Write;
+
+use crate::{
+    fs::{
+        path::{Dentry, MountNamespace, MountNode, MountPropagation},
+        procfs::template::{FileOps, ProcFileBuilder},
+        utils::Inode,
+    },
+    prelude::*,
+    process::posix_thread::AsPosixThread,
+    Process,
+};
+
+/// Represents the inode at `/proc/[pid]/mounts`.
+pub struct MountsFileOps(Arc<Process>);
+
+impl MountsFileOps {
+    pub fn new_inode(process_ref: Arc<Process>, parent: Weak<dyn Inode>) -> Arc<dyn Inode> {
+        ProcFileBuilder::new(Self(process_ref))
+            .parent(parent)
+            .build()
+            .unwrap()
+    }
+}
+
+impl FileOps for MountsFileOps {
+    fn data(&self) -> Result<Vec<u8>> {
+        let mut output = String::new();
+        for mount_node in mnt_ns_of(&self.0).mounts() {
+            let fs_name = mount_node.fs().name();
+            writeln!(
+                output,
+                "{} {} {} rw 0 0",
+                fs_name,
+                mount_point_of(&mount_node),
+                fs_name
+            )
+            .unwrap();
+        }
+        Ok(output.into_bytes())
+    }
+}
+
+/// Represents the inode at `/proc/[pid]/mountinfo`.
+pub struct MountInfoFileOps(Arc<Process>);
+
+impl MountInfoFileOps {
+    pub fn new_inode(process_ref: Arc<Process>, parent: Weak<dyn Inode>) -> Arc<dyn Inode> {
+        ProcFileBuilder::new(Self(process_ref))
+            .parent(parent)
+            .build()
+            .unwrap()
+    }
+}
+
+impl FileOps for MountInfoFileOps {
+    fn data(&self) -> Result<Vec<u8>> {
+        let mut output = String::new();
+        for mount_node in mnt_ns_of(&self.0).mounts() {
+            let parent_id = mount_node
+                .parent()
+                .and_then(|parent| parent.upgrade())
+                .map_or(mount_node.id(), |parent| parent.id());
+            let optional_fields = match mount_node.propagation() {
+                MountPropagation::Private => String::new(),
+                MountPropagation::Shared(peer_group) => format!(" shared:{}", peer_group.id()),
+                MountPropagation::Slave(peer_group) => format!(" master:{}", peer_group.id()),
+                MountPropagation::Unbindable => String::from(" unbindable"),
+            };
+            let fs_name = mount_node.fs().name();
+            writeln!(
+                output,
+                "{} {} 0:0 {} {} rw{} - {} {} rw",
+                mount_node.id(),
+                parent_id,
+                mount_node.root_path(),
+                mount_point_of(&mount_node),
+                optional_fields,
+                fs_name,
+                fs_name
+            )
+            .unwrap();
+        }
+        Ok(output.into_bytes())
+    }
+}
+
+fn mnt_ns_of(process: &Process) -> Arc<MountNamespace> {
+    let main_thread = process.main_thread();
+    let posix_thread = main_thread.as_posix_thread().unwrap();
+    let ns_proxy = posix_thread.ns_proxy().lock();
+    ns_proxy.mnt_ns().clone()
+}
+
+fn mount_point_of(mount_node: &Arc<MountNode>) -> String {
+    Dentry::new_fs_root(mount_node.clone()).abs_path()
+}
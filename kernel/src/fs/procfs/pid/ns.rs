@@ -0,0 +1,133 @@
+// SPDX-License-Identifier: MPL-2.0
+
+use crate::{
+    fs::{
+        procfs::template::{DirOps, FileOps, ProcDir, ProcDirBuilder, ProcFile, ProcFileBuilder},
+        utils::{DirEntryVecExt, Inode},
+    },
+    prelude::*,
+    process::{namespace::Namespace, posix_thread::AsPosixThread},
+    Process,
+};
+
+/// Represents the inode at `/proc/[pid]/ns`.
+pub struct NsDirOps(Arc<Process>);
+
+impl NsDirOps {
+    pub fn new_inode(process_ref: Arc<Process>, parent: Weak<dyn Inode>) -> Arc<dyn Inode> {
+        ProcDirBuilder::new(Self(process_ref))
+            .parent(parent)
+            .build()
+            .unwrap()
+    }
+}
+
+impl DirOps for NsDirOps {
+    fn lookup_child(&self, this_ptr: Weak<dyn Inode>, name: &str) -> Result<Arc<dyn Inode>> {
+        let Some(kind) = NsKind::from_name(name) else {
+            return_errno!(Errno::ENOENT);
+        };
+        Ok(NsFileOps::new_inode(self.0.clone(), kind, this_ptr))
+    }
+
+    fn populate_children(&self, this_ptr: Weak<dyn Inode>) {
+        let this = {
+            let this = this_ptr.upgrade().unwrap();
+            this.downcast_ref::<ProcDir<NsDirOps>>().unwrap().this()
+        };
+        let mut cached_children = this.cached_children().write();
+        for kind in NsKind::ALL {
+            cached_children.put_entry_if_not_found(kind.name(), || {
+                NsFileOps::new_inode(self.0.clone(), kind, this_ptr.clone())
+            });
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+enum NsKind {
+    Ipc,
+    Mnt,
+    Pid,
+    PidForChildren,
+    Uts,
+}
+
+impl NsKind {
+    const ALL: [Self; 5] = [
+        Self::Ipc,
+        Self::Mnt,
+        Self::Pid,
+        Self::PidForChildren,
+        Self::Uts,
+    ];
+
+    fn name(self) -> &'static str {
+        match self {
+            Self::Ipc => "ipc",
+            Self::Mnt => "mnt",
+            Self::Pid => "pid",
+            Self::PidForChildren => "pid_for_children",
+            Self::Uts => "uts",
+        }
+    }
+
+    fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|kind| kind.name() == name)
+    }
+}
+
+/// Represents the inode at `/proc/[pid]/ns/[name]`.
+///
+/// The opened file refers to the namespace and can be passed to `setns(2)`.
+//
+// TODO: In Linux, these are symbolic links to the inodes in the namespace file system, which we
+// cannot support until magic links (i.e., links that are followed without resolving the target
+// path) are supported.
+struct NsFileOps {
+    process: Arc<Process>,
+    kind: NsKind,
+}
+
+impl NsFileOps {
+    pub fn new_inode(
+        process: Arc<Process>,
+        kind: NsKind,
+        parent: Weak<dyn Inode>,
+    ) -> Arc<dyn Inode> {
+        ProcFileBuilder::new(Self { process, kind })
+            .parent(parent)
+            .build()
+            .unwrap()
+    }
+
+    fn namespace(&self) -> Namespace {
+        let ns_proxy = || {
+            let main_thread = self.process.main_thread();
+            let ns_proxy = main_thread.as_posix_thread().unwrap().ns_proxy().lock();
+            ns_proxy.clone()
+        };
+
+        match self.kind {
+            NsKind::Ipc => Namespace::Ipc(ns_proxy().ipc_ns().clone()),
+            NsKind::Mnt => Namespace::Mnt(ns_proxy().mnt_ns().clone()),
+            NsKind::Pid => Namespace::Pid(self.process.pid_ns().clone()),
+            NsKind::PidForChildren => Namespace::Pid(ns_proxy().pid_ns_for_children().clone()),
+            NsKind::Uts => Namespace::Uts(ns_proxy().uts_ns().clone()),
+        }
+    }
+}
+
+impl FileOps for NsFileOps {
+    fn data(&self) -> Result<Vec<u8>> {
+        return_errno_with_message!(Errno::EINVAL, "the namespace file cannot be read");
+    }
+}
+
+/// Returns the namespace that the inode at `/proc/[pid]/ns/[name]` refers to.
+///
+/// This method returns `None` if the inode is not such an inode.
+pub fn namespace_of(inode: &dyn Inode) -> Option<Namespace> {
+    let ns_file = inode.downcast_ref::<ProcFile<NsFileOps>>()?;
+    Some(ns_file.inner().namespace())
+}
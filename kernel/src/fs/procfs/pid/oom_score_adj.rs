@@ -0,0 +1,62 @@
+// SPDX-License-Identifier: MPL-2.0
+
+use alloc::format;
+
+use crate::{
+    fs::{
+        procfs::template::{FileOps, ProcFileBuilder},
+        utils::{Inode, InodeMode},
+    },
+    prelude::*,
+    process::{
+        credentials::capabilities::CapSet,
+        oom::{OOM_SCORE_ADJ_MAX, OOM_SCORE_ADJ_MIN},
+        posix_thread::AsPosixThread,
+        Process,
+    },
+};
+
+/// Represents the inode at `/proc/[pid]/oom_score_adj`.
+pub struct OomScoreAdjFileOps(Arc<Process>);
+
+impl OomScoreAdjFileOps {
+    pub fn new_inode(process_ref: Arc<Process>, parent: Weak<dyn Inode>) -> Arc<dyn Inode> {
+        ProcFileBuilder::new(Self(process_ref))
+            .parent(parent)
+            .mode(InodeMode::from_bits_truncate(0o644))
+            .build()
+            .unwrap()
+    }
+}
+
+impl FileOps for OomScoreAdjFileOps {
+    fn data(&self) -> Result<Vec<u8>> {
+        let output = format!("{}\n", self.0.oom_score_adj());
+        Ok(output.into_bytes())
+    }
+
+    fn write_data(&self, data: &[u8]) -> Result<()> {
+        let oom_score_adj = core::str::from_utf8(data)
+            .ok()
+            .and_then(|data| data.trim().parse::<i16>().ok())
+            .filter(|adj| (OOM_SCORE_ADJ_MIN..=OOM_SCORE_ADJ_MAX).contains(adj))
+            .ok_or_else(|| Error::with_message(Errno::EINVAL, "the value is invalid"))?;
+
+        if oom_score_adj < self.0.oom_score_adj() {
+            let current = current_thread!();
+            let credentials = current.as_posix_thread().unwrap().credentials();
+            if !credentials
+                .effective_capset()
+                .contains(CapSet::SYS_RESOURCE)
+            {
+                return_errno_with_message!(
+                    Errno::EACCES,
+                    "decreasing oom_score_adj requires CAP_SYS_RESOURCE"
+                );
+            }
+        }
+
+        self.0.set_oom_score_adj(oom_score_adj);
+        Ok(())
+    }
+}
@@ -0,0 +1,88 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! This module offers `/proc/[pid]/pagemap`, which describes the mapped pages of a process.
+//!
+//! The file contains one 64-bit entry for each virtual page, where the entry of the page at
+//! `vaddr` is located at offset `vaddr / PAGE_SIZE * 8`.
+//!
+//! Reference: <https://docs.kernel.org/admin-guide/mm/pagemap.html>
+
+use ostd::mm::MAX_USERSPACE_VADDR;
+
+use crate::{
+    fs::{
+        procfs::template::{FileOps, ProcFileBuilder},
+        utils::{Inode, InodeMode},
+    },
+    prelude::*,
+    process::{credentials::capabilities::CapSet, posix_thread::AsPosixThread},
+    Process,
+};
+
+/// Represents the inode at `/proc/[pid]/pagemap`.
+pub struct PagemapFileOps(Arc<Process>);
+
+impl PagemapFileOps {
+    pub fn new_inode(process_ref: Arc<Process>, parent: Weak<dyn Inode>) -> Arc<dyn Inode> {
+        ProcFileBuilder::new(Self(process_ref))
+            .parent(parent)
+            .mode(InodeMode::from_bits_truncate(0o400))
+            .build()
+            .unwrap()
+    }
+}
+
+const ENTRY_SIZE: usize = size_of::<u64>();
+
+const PM_PFN_MASK: u64 = (1 << 55) - 1;
+const PM_MMAP_EXCLUSIVE: u64 = 1 << 56;
+const PM_FILE: u64 = 1 << 61;
+const PM_PRESENT: u64 = 1 << 63;
+
+impl FileOps for PagemapFileOps {
+    fn data(&self) -> Result<Vec<u8>> {
+        // The whole file is too large to be generated at once.
+        return_errno_with_message!(Errno::EINVAL, "the file must be read at offsets");
+    }
+
+    fn read_at(&self, offset: usize, writer: &mut VmWriter) -> Result<usize> {
+        if offset % ENTRY_SIZE != 0 || writer.avail() % ENTRY_SIZE != 0 {
+            return_errno_with_message!(Errno::EINVAL, "the read is not aligned to the entries");
+        }
+
+        let nr_pages = MAX_USERSPACE_VADDR / PAGE_SIZE;
+        let start_page = offset / ENTRY_SIZE;
+        let end_page = (start_page + writer.avail() / ENTRY_SIZE).min(nr_pages);
+        if start_page >= end_page {
+            return Ok(0);
+        }
+
+        // Like Linux, the physical frame numbers are only visible with `CAP_SYS_ADMIN`.
+        let shows_pfn = {
+            let current = current_thread!();
+            let credentials = current.as_posix_thread().unwrap().credentials();
+            credentials.effective_capset().contains(CapSet::SYS_ADMIN)
+        };
+
+        let mut entries = vec![0u64; end_page - start_page];
+        let range = start_page * PAGE_SIZE..end_page * PAGE_SIZE;
+        for (vaddr, page) in self.0.root_vmar().query_pages(range) {
+            let mut entry = PM_PRESENT;
+            if shows_pfn {
+                entry |= (page.paddr / PAGE_SIZE) as u64 & PM_PFN_MASK;
+            }
+            if page.is_file_or_shared {
+                entry |= PM_FILE;
+            }
+            if page.is_exclusive {
+                entry |= PM_MMAP_EXCLUSIVE;
+            }
+            entries[vaddr / PAGE_SIZE - start_page] = entry;
+        }
+
+        for entry in entries.iter() {
+            writer.write_fallible(&mut entry.as_bytes().into())?;
+        }
+        Ok(entries.len() * ENTRY_SIZE)
+    }
+}
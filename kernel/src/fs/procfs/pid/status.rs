@@ -1,6 +1,11 @@
 // SPDX-License-Identifier: MPL-2.0
 
-use core::fmt::Write;
+use core::fmt::{self, Write};
+
+use ostd::{
+    cpu::{num_cpus, CpuSet},
+    mm::MAX_USERSPACE_VADDR,
+};
 
 use crate::{
     fs::{
@@ -9,6 +14,7 @@ use crate::{
     },
     prelude::*,
     process::posix_thread::AsPosixThread,
+    sched::cpuset::CpuList,
     Process,
 };
 
@@ -73,7 +79,13 @@ impl FileOps for StatusFileOps {
     fn data(&self) -> Result<Vec<u8>> {
         let process = &self.0;
         let main_thread = process.main_thread();
-        let file_table = main_thread.as_posix_thread().unwrap().file_table();
+        let nr_fds = main_thread
+            .as_posix_thread()
+            .unwrap()
+            .file_table()
+            .lock()
+            .read()
+            .len();
 
         let mut status_output = String::new();
         writeln!(status_output, "Name:\t{}", process.executable_path()).unwrap();
@@ -81,13 +93,68 @@ impl FileOps for StatusFileOps {
         writeln!(status_output, "Pid:\t{}", process.pid()).unwrap();
         writeln!(status_output, "PPid:\t{}", process.parent().pid()).unwrap();
         writeln!(status_output, "TracerPid:\t{}", process.parent().pid()).unwrap(); // Assuming TracerPid is the same as PPid
-        writeln!(status_output, "FDSize:\t{}", file_table.read().len()).unwrap();
+        writeln!(status_output, "FDSize:\t{}", nr_fds).unwrap();
         writeln!(
             status_output,
             "Threads:\t{}",
             process.tasks().lock().as_slice().len()
         )
         .unwrap();
+
+        let locked_size = process.root_vmar().locked_size(0..MAX_USERSPACE_VADDR);
+        writeln!(status_output, "VmLck:\t{} kB", locked_size / 1024).unwrap();
+        writeln!(status_output, "VmPin:\t{} kB", process.pinned_size() / 1024).unwrap();
+
+        let credentials = main_thread.as_posix_thread().unwrap().credentials();
+        for (name, capset) in [
+            ("CapInh", credentials.inheritable_capset()),
+            ("CapPrm", credentials.permitted_capset()),
+            ("CapEff", credentials.effective_capset()),
+            ("CapBnd", credentials.bounding_capset()),
+            ("CapAmb", credentials.ambient_capset()),
+        ] {
+            writeln!(status_output, "{}:\t{:016x}", name, capset.bits()).unwrap();
+        }
+
+        let cpu_affinity = main_thread.atomic_cpu_affinity().load();
+        writeln!(status_output, "Cpus_allowed:\t{}", CpuMask(&cpu_affinity)).unwrap();
+        writeln!(
+            status_output,
+            "Cpus_allowed_list:\t{}",
+            CpuList(&cpu_affinity)
+        )
+        .unwrap();
+
         Ok(status_output.into_bytes())
     }
 }
+
+/// Formats a CPU set as a hexadecimal mask, where every 32 bits are separated by commas.
+struct CpuMask<'a>(&'a CpuSet);
+
+impl fmt::Display for CpuMask<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        const BITS_PER_GROUP: usize = 32;
+
+        let num_cpus = num_cpus();
+        let num_groups = num_cpus.div_ceil(BITS_PER_GROUP);
+
+        let mut groups = vec![0u32; num_groups];
+        for cpu in self.0.iter() {
+            let id = cpu.as_usize();
+            groups[id / BITS_PER_GROUP] |= 1 << (id % BITS_PER_GROUP);
+        }
+
+        for (i, group) in groups.iter().enumerate().rev() {
+            if i == num_groups - 1 {
+                // The highest group only has enough digits for the remaining CPUs.
+                let width = (num_cpus - i * BITS_PER_GROUP).div_ceil(4);
+                write!(f, "{:0width$x}", group, width = width)?;
+            } else {
+                write!(f, ",{:08x}", group)?;
+            }
+        }
+
+        Ok(())
+    }
+}
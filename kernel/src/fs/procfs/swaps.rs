@@ -0,0 +1,42 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! This module offers `/proc/swaps` file support, which tells the user space
+//! about the active swap areas.
+//!
+//! Reference: <https://man7.org/linux/man-pages/man5/proc_swaps.5.html>
+
+use alloc::format;
+
+use crate::{
+    fs::{
+        procfs::template::{FileOps, ProcFileBuilder},
+        utils::Inode,
+    },
+    prelude::*,
+    vm::swap::swap_areas,
+};
+
+/// Represents the inode at `/proc/swaps`.
+pub struct SwapsFileOps;
+
+impl SwapsFileOps {
+    pub fn new_inode(parent: Weak<dyn Inode>) -> Arc<dyn Inode> {
+        ProcFileBuilder::new(Self).parent(parent).build().unwrap()
+    }
+}
+
+impl FileOps for SwapsFileOps {
+    fn data(&self) -> Result<Vec<u8>> {
+        let mut output = String::from("Filename\t\t\t\tType\t\tSize\t\tUsed\t\tPriority\n");
+        for area in swap_areas() {
+            output.push_str(&format!(
+                "{:<40}file\t\t{}\t\t{}\t\t{}\n",
+                area.dentry().abs_path(),
+                area.size() / 1024,
+                area.used() / 1024,
+                area.priority(),
+            ));
+        }
+        Ok(output.into_bytes())
+    }
+}
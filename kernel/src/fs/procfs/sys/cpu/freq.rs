@@ -0,0 +1,159 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! This module offers the files that show the frequencies of the CPUs and
+//! select the frequency governor.
+//!
+//! The files are placed in `/proc/sys/cpu/cpu<N>/cpufreq`, while they are in
+//! `/sys/devices/system/cpu/cpu<N>/cpufreq` on Linux. Unlike Linux, the
+//! governor is shared by all the CPUs, so writing `scaling_governor` of any CPU
+//! changes the governor of all the CPUs.
+//!
+//! Reference: <https://docs.kernel.org/admin-guide/pm/cpufreq.html>
+
+use alloc::format;
+
+use ostd::cpu::{freq, CpuId};
+
+use crate::{
+    fs::{
+        procfs::{
+            template::{DirOps, FileOps, ProcDirBuilder, ProcFileBuilder},
+            ProcDir,
+        },
+        utils::{DirEntryVecExt, Inode, InodeMode},
+    },
+    prelude::*,
+};
+
+/// Represents the inode at `/proc/sys/cpu/cpu<N>/cpufreq`.
+pub(super) struct CpuFreqDirOps(CpuId);
+
+impl CpuFreqDirOps {
+    pub(super) fn new_inode(cpu_id: CpuId, parent: Weak<dyn Inode>) -> Arc<dyn Inode> {
+        ProcDirBuilder::new(Self(cpu_id))
+            .parent(parent)
+            .build()
+            .unwrap()
+    }
+}
+
+impl DirOps for CpuFreqDirOps {
+    fn lookup_child(&self, this_ptr: Weak<dyn Inode>, name: &str) -> Result<Arc<dyn Inode>> {
+        let Some(file) = CpuFreqFile::from_name(name) else {
+            return_errno!(Errno::ENOENT);
+        };
+        Ok(CpuFreqFileOps::new_inode(self.0, file, this_ptr))
+    }
+
+    fn populate_children(&self, this_ptr: Weak<dyn Inode>) {
+        let this = {
+            let this = this_ptr.upgrade().unwrap();
+            this.downcast_ref::<ProcDir<CpuFreqDirOps>>()
+                .unwrap()
+                .this()
+        };
+        let mut cached_children = this.cached_children().write();
+        for file in CpuFreqFile::ALL {
+            cached_children.put_entry_if_not_found(file.name(), || {
+                CpuFreqFileOps::new_inode(self.0, file, this_ptr.clone())
+            });
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+enum CpuFreqFile {
+    CpuinfoMinFreq,
+    CpuinfoMaxFreq,
+    BaseFrequency,
+    ScalingCurFreq,
+    ScalingDriver,
+    ScalingGovernor,
+    ScalingAvailableGovernors,
+}
+
+impl CpuFreqFile {
+    const ALL: [Self; 7] = [
+        Self::CpuinfoMinFreq,
+        Self::CpuinfoMaxFreq,
+        Self::BaseFrequency,
+        Self::ScalingCurFreq,
+        Self::ScalingDriver,
+        Self::ScalingGovernor,
+        Self::ScalingAvailableGovernors,
+    ];
+
+    fn name(self) -> &'static str {
+        match self {
+            Self::CpuinfoMinFreq => "cpuinfo_min_freq",
+            Self::CpuinfoMaxFreq => "cpuinfo_max_freq",
+            Self::BaseFrequency => "base_frequency",
+            Self::ScalingCurFreq => "scaling_cur_freq",
+            Self::ScalingDriver => "scaling_driver",
+            Self::ScalingGovernor => "scaling_governor",
+            Self::ScalingAvailableGovernors => "scaling_available_governors",
+        }
+    }
+
+    fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|file| file.name() == name)
+    }
+}
+
+/// Represents the inodes at `/proc/sys/cpu/cpu<N>/cpufreq/*`.
+struct CpuFreqFileOps {
+    cpu_id: CpuId,
+    file: CpuFreqFile,
+}
+
+impl CpuFreqFileOps {
+    fn new_inode(cpu_id: CpuId, file: CpuFreqFile, parent: Weak<dyn Inode>) -> Arc<dyn Inode> {
+        let mode = match file {
+            CpuFreqFile::ScalingGovernor => 0o644,
+            _ => 0o444,
+        };
+        ProcFileBuilder::new(Self { cpu_id, file })
+            .parent(parent)
+            .mode(InodeMode::from_bits_truncate(mode))
+            .build()
+            .unwrap()
+    }
+}
+
+impl FileOps for CpuFreqFileOps {
+    fn data(&self) -> Result<Vec<u8>> {
+        let info = freq::info();
+
+        // Like Linux, the frequencies are in kHz.
+        let output = match self.file {
+            CpuFreqFile::CpuinfoMinFreq => format!("{}\n", info.min_khz),
+            CpuFreqFile::CpuinfoMaxFreq => format!("{}\n", info.max_khz),
+            CpuFreqFile::BaseFrequency => format!("{}\n", info.base_khz),
+            CpuFreqFile::ScalingCurFreq => format!("{}\n", freq::cur_freq_khz(self.cpu_id)),
+            CpuFreqFile::ScalingDriver => {
+                format!("{}\n", freq::driver_name().unwrap_or("none"))
+            }
+            CpuFreqFile::ScalingGovernor => format!("{}\n", freq::governor().name()),
+            CpuFreqFile::ScalingAvailableGovernors => {
+                let names = freq::available_governors()
+                    .iter()
+                    .map(|governor| governor.name())
+                    .collect::<Vec<_>>();
+                format!("{}\n", names.join(" "))
+            }
+        };
+        Ok(output.into_bytes())
+    }
+
+    fn write_data(&self, data: &[u8]) -> Result<()> {
+        if !matches!(self.file, CpuFreqFile::ScalingGovernor) {
+            return_errno!(Errno::EPERM);
+        }
+
+        let name = core::str::from_utf8(data)
+            .map_err(|_| Error::with_message(Errno::EINVAL, "the governor is invalid"))?;
+        freq::set_governor(name.trim())
+            .map_err(|_| Error::with_message(Errno::EINVAL, "the governor does not exist"))?;
+        Ok(())
+    }
+}
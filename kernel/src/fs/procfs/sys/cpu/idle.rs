@@ -0,0 +1,250 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! This module offers the files that show the idle states of the CPUs and
+//! control halt-polling.
+//!
+//! The files are placed in `/proc/sys/cpu/cpuidle` and `/proc/sys/cpu/cpu<N>/cpuidle`, while
+//! they are in `/sys/devices/system/cpu/cpuidle` and `/sys/devices/system/cpu/cpu<N>/cpuidle`
+//! on Linux. The `halt_poll_ns` file corresponds to Linux's
+//! `/sys/module/haltpoll/parameters/guest_halt_poll_ns`.
+//!
+//! Reference: <https://docs.kernel.org/admin-guide/pm/cpuidle.html>
+
+use alloc::format;
+
+use ostd::cpu::{idle, CpuId};
+
+use crate::{
+    fs::{
+        procfs::{
+            template::{DirOps, FileOps, ProcDirBuilder, ProcFileBuilder},
+            ProcDir,
+        },
+        utils::{DirEntryVecExt, Inode, InodeMode},
+    },
+    prelude::*,
+};
+
+/// Represents the inode at `/proc/sys/cpu/cpuidle`.
+pub(super) struct CpuIdleDirOps;
+
+impl CpuIdleDirOps {
+    pub(super) fn new_inode(parent: Weak<dyn Inode>) -> Arc<dyn Inode> {
+        ProcDirBuilder::new(Self).parent(parent).build().unwrap()
+    }
+}
+
+impl DirOps for CpuIdleDirOps {
+    fn lookup_child(&self, this_ptr: Weak<dyn Inode>, name: &str) -> Result<Arc<dyn Inode>> {
+        if name == "halt_poll_ns" {
+            return Ok(HaltPollFileOps::new_inode(this_ptr));
+        }
+        return_errno!(Errno::ENOENT);
+    }
+
+    fn populate_children(&self, this_ptr: Weak<dyn Inode>) {
+        let this = {
+            let this = this_ptr.upgrade().unwrap();
+            this.downcast_ref::<ProcDir<CpuIdleDirOps>>()
+                .unwrap()
+                .this()
+        };
+        let mut cached_children = this.cached_children().write();
+        cached_children.put_entry_if_not_found("halt_poll_ns", || {
+            HaltPollFileOps::new_inode(this_ptr.clone())
+        });
+    }
+}
+
+/// Represents the inode at `/proc/sys/cpu/cpuidle/halt_poll_ns`.
+struct HaltPollFileOps;
+
+impl HaltPollFileOps {
+    fn new_inode(parent: Weak<dyn Inode>) -> Arc<dyn Inode> {
+        ProcFileBuilder::new(Self)
+            .parent(parent)
+            .mode(InodeMode::from_bits_truncate(0o644))
+            .build()
+            .unwrap()
+    }
+}
+
+impl FileOps for HaltPollFileOps {
+    fn data(&self) -> Result<Vec<u8>> {
+        let output = format!("{}\n", idle::max_poll_ns());
+        Ok(output.into_bytes())
+    }
+
+    fn write_data(&self, data: &[u8]) -> Result<()> {
+        let max_poll_ns = core::str::from_utf8(data)
+            .ok()
+            .and_then(|data| data.trim().parse::<u64>().ok())
+            .ok_or_else(|| Error::with_message(Errno::EINVAL, "the value is invalid"))?;
+        idle::set_max_poll_ns(max_poll_ns);
+        Ok(())
+    }
+}
+
+/// Represents the inode at `/proc/sys/cpu/cpu<N>/cpuidle`.
+pub(super) struct CpuIdIdleDirOps(CpuId);
+
+impl CpuIdIdleDirOps {
+    pub(super) fn new_inode(cpu_id: CpuId, parent: Weak<dyn Inode>) -> Arc<dyn Inode> {
+        ProcDirBuilder::new(Self(cpu_id))
+            .parent(parent)
+            .build()
+            .unwrap()
+    }
+}
+
+impl DirOps for CpuIdIdleDirOps {
+    fn lookup_child(&self, this_ptr: Weak<dyn Inode>, name: &str) -> Result<Arc<dyn Inode>> {
+        let Some(index) = name
+            .strip_prefix("state")
+            .and_then(|index| index.parse::<usize>().ok())
+            .filter(|index| *index < idle::states().len())
+        else {
+            return_errno!(Errno::ENOENT);
+        };
+        Ok(IdleStateDirOps::new_inode(self.0, index, this_ptr))
+    }
+
+    fn populate_children(&self, this_ptr: Weak<dyn Inode>) {
+        let this = {
+            let this = this_ptr.upgrade().unwrap();
+            this.downcast_ref::<ProcDir<CpuIdIdleDirOps>>()
+                .unwrap()
+                .this()
+        };
+        let mut cached_children = this.cached_children().write();
+        for index in 0..idle::states().len() {
+            cached_children.put_entry_if_not_found(&format!("state{}", index), || {
+                IdleStateDirOps::new_inode(self.0, index, this_ptr.clone())
+            });
+        }
+    }
+}
+
+/// Represents the inode at `/proc/sys/cpu/cpu<N>/cpuidle/state<K>`.
+struct IdleStateDirOps {
+    cpu_id: CpuId,
+    index: usize,
+}
+
+impl IdleStateDirOps {
+    fn new_inode(cpu_id: CpuId, index: usize, parent: Weak<dyn Inode>) -> Arc<dyn Inode> {
+        ProcDirBuilder::new(Self { cpu_id, index })
+            .parent(parent)
+            .build()
+            .unwrap()
+    }
+
+    fn new_file_inode(&self, file: IdleStateFile, parent: Weak<dyn Inode>) -> Arc<dyn Inode> {
+        IdleStateFileOps::new_inode(self.cpu_id, self.index, file, parent)
+    }
+}
+
+impl DirOps for IdleStateDirOps {
+    fn lookup_child(&self, this_ptr: Weak<dyn Inode>, name: &str) -> Result<Arc<dyn Inode>> {
+        let Some(file) = IdleStateFile::from_name(name) else {
+            return_errno!(Errno::ENOENT);
+        };
+        Ok(self.new_file_inode(file, this_ptr))
+    }
+
+    fn populate_children(&self, this_ptr: Weak<dyn Inode>) {
+        let this = {
+            let this = this_ptr.upgrade().unwrap();
+            this.downcast_ref::<ProcDir<IdleStateDirOps>>()
+                .unwrap()
+                .this()
+        };
+        let mut cached_children = this.cached_children().write();
+        for file in IdleStateFile::ALL {
+            cached_children.put_entry_if_not_found(file.name(), || {
+                self.new_file_inode(file, this_ptr.clone())
+            });
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+enum IdleStateFile {
+    Name,
+    Desc,
+    Latency,
+    Residency,
+    Usage,
+    Time,
+}
+
+impl IdleStateFile {
+    const ALL: [Self; 6] = [
+        Self::Name,
+        Self::Desc,
+        Self::Latency,
+        Self::Residency,
+        Self::Usage,
+        Self::Time,
+    ];
+
+    fn name(self) -> &'static str {
+        match self {
+            Self::Name => "name",
+            Self::Desc => "desc",
+            Self::Latency => "latency",
+            Self::Residency => "residency",
+            Self::Usage => "usage",
+            Self::Time => "time",
+        }
+    }
+
+    fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|file| file.name() == name)
+    }
+}
+
+/// Represents the inodes at `/proc/sys/cpu/cpu<N>/cpuidle/state<K>/*`.
+struct IdleStateFileOps {
+    cpu_id: CpuId,
+    index: usize,
+    file: IdleStateFile,
+}
+
+impl IdleStateFileOps {
+    fn new_inode(
+        cpu_id: CpuId,
+        index: usize,
+        file: IdleStateFile,
+        parent: Weak<dyn Inode>,
+    ) -> Arc<dyn Inode> {
+        ProcFileBuilder::new(Self {
+            cpu_id,
+            index,
+            file,
+        })
+        .parent(parent)
+        .mode(InodeMode::from_bits_truncate(0o444))
+        .build()
+        .unwrap()
+    }
+}
+
+impl FileOps for IdleStateFileOps {
+    fn data(&self) -> Result<Vec<u8>> {
+        let state = &idle::states()[self.index];
+
+        // Like Linux, the times are in microseconds.
+        let output = match self.file {
+            IdleStateFile::Name => format!("{}\n", state.name()),
+            IdleStateFile::Desc => format!("{}\n", state.desc()),
+            IdleStateFile::Latency => format!("{}\n", state.exit_latency_ns() / 1000),
+            IdleStateFile::Residency => format!("{}\n", state.target_residency_ns() / 1000),
+            IdleStateFile::Usage => format!("{}\n", idle::usage(self.cpu_id, self.index).usage),
+            IdleStateFile::Time => {
+                format!("{}\n", idle::usage(self.cpu_id, self.index).time_ns / 1000)
+            }
+        };
+        Ok(output.into_bytes())
+    }
+}
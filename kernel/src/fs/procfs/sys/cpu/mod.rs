@@ -0,0 +1,131 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! This module offers the files that show and control the CPUs.
+//!
+//! The files are placed in `/proc/sys/cpu`, while they are in `/sys/devices/system/cpu` on
+//! Linux.
+//!
+//! Reference: <https://docs.kernel.org/admin-guide/cputopology.html>
+
+use alloc::format;
+
+use ostd::cpu::{num_cpus, CpuId};
+
+use self::{
+    freq::CpuFreqDirOps,
+    idle::{CpuIdIdleDirOps, CpuIdleDirOps},
+    online::{CpuListFileOps, CpuListKind, CpuOnlineFileOps},
+};
+use crate::{
+    fs::{
+        procfs::{
+            template::{DirOps, ProcDirBuilder},
+            ProcDir,
+        },
+        utils::{DirEntryVecExt, Inode},
+    },
+    prelude::*,
+};
+
+mod freq;
+mod idle;
+mod online;
+
+/// Represents the inode at `/proc/sys/cpu`.
+pub struct CpuDirOps;
+
+impl CpuDirOps {
+    pub fn new_inode(parent: Weak<dyn Inode>) -> Arc<dyn Inode> {
+        ProcDirBuilder::new(Self).parent(parent).build().unwrap()
+    }
+}
+
+impl DirOps for CpuDirOps {
+    fn lookup_child(&self, this_ptr: Weak<dyn Inode>, name: &str) -> Result<Arc<dyn Inode>> {
+        if let Some(kind) = CpuListKind::from_name(name) {
+            return Ok(CpuListFileOps::new_inode(kind, this_ptr));
+        }
+        if name == "cpuidle" {
+            return Ok(CpuIdleDirOps::new_inode(this_ptr));
+        }
+
+        let Some(cpu_id) = name
+            .strip_prefix("cpu")
+            .and_then(|id| id.parse::<usize>().ok())
+            .and_then(|id| CpuId::try_from(id).ok())
+        else {
+            return_errno!(Errno::ENOENT);
+        };
+        Ok(CpuIdDirOps::new_inode(cpu_id, this_ptr))
+    }
+
+    fn populate_children(&self, this_ptr: Weak<dyn Inode>) {
+        let this = {
+            let this = this_ptr.upgrade().unwrap();
+            this.downcast_ref::<ProcDir<CpuDirOps>>().unwrap().this()
+        };
+        let mut cached_children = this.cached_children().write();
+        for kind in CpuListKind::ALL {
+            cached_children.put_entry_if_not_found(kind.name(), || {
+                CpuListFileOps::new_inode(kind, this_ptr.clone())
+            });
+        }
+        cached_children
+            .put_entry_if_not_found("cpuidle", || CpuIdleDirOps::new_inode(this_ptr.clone()));
+        for id in 0..num_cpus() {
+            let cpu_id = CpuId::try_from(id).unwrap();
+            cached_children.put_entry_if_not_found(&format!("cpu{}", id), || {
+                CpuIdDirOps::new_inode(cpu_id, this_ptr.clone())
+            });
+        }
+    }
+}
+
+/// Represents the inode at `/proc/sys/cpu/cpu<N>`.
+struct CpuIdDirOps(CpuId);
+
+impl CpuIdDirOps {
+    fn new_inode(cpu_id: CpuId, parent: Weak<dyn Inode>) -> Arc<dyn Inode> {
+        ProcDirBuilder::new(Self(cpu_id))
+            .parent(parent)
+            .build()
+            .unwrap()
+    }
+
+    /// Returns whether the CPU can go offline.
+    ///
+    /// Like Linux on x86, the BSP has no `online` file since it cannot go offline.
+    fn is_hotpluggable(&self) -> bool {
+        self.0 != CpuId::bsp()
+    }
+}
+
+impl DirOps for CpuIdDirOps {
+    fn lookup_child(&self, this_ptr: Weak<dyn Inode>, name: &str) -> Result<Arc<dyn Inode>> {
+        match name {
+            "cpufreq" => Ok(CpuFreqDirOps::new_inode(self.0, this_ptr)),
+            "cpuidle" => Ok(CpuIdIdleDirOps::new_inode(self.0, this_ptr)),
+            "online" if self.is_hotpluggable() => Ok(CpuOnlineFileOps::new_inode(self.0, this_ptr)),
+            _ => return_errno!(Errno::ENOENT),
+        }
+    }
+
+    fn populate_children(&self, this_ptr: Weak<dyn Inode>) {
+        let this = {
+            let this = this_ptr.upgrade().unwrap();
+            this.downcast_ref::<ProcDir<CpuIdDirOps>>().unwrap().this()
+        };
+        let mut cached_children = this.cached_children().write();
+        cached_children.put_entry_if_not_found("cpufreq", || {
+            CpuFreqDirOps::new_inode(self.0, this_ptr.clone())
+        });
+        cached_children.put_entry_if_not_found("cpuidle", || {
+            CpuIdIdleDirOps::new_inode(self.0, this_ptr.clone())
+        });
+        if self.is_hotpluggable() {
+            cached_children.put_entry_if_not_found("online", || {
+                CpuOnlineFileOps::new_inode(self.0, this_ptr.clone())
+            });
+        }
+    }
+}
@@ -0,0 +1,114 @@
+// SPDX-License-Identifier: MPL-2.0
+
+use alloc::format;
+
+use ostd::cpu::{hotplug, CpuId, CpuSet};
+
+use crate::{
+    fs::{
+        procfs::template::{FileOps, ProcFileBuilder},
+        utils::{Inode, InodeMode},
+    },
+    prelude::*,
+    sched::cpuset::CpuList,
+};
+
+/// The lists of CPUs in `/proc/sys/cpu`.
+#[derive(Debug, Clone, Copy)]
+pub(super) enum CpuListKind {
+    Online,
+    Offline,
+    Possible,
+    Present,
+}
+
+impl CpuListKind {
+    pub(super) const ALL: [Self; 4] = [Self::Online, Self::Offline, Self::Possible, Self::Present];
+
+    pub(super) fn name(self) -> &'static str {
+        match self {
+            Self::Online => "online",
+            Self::Offline => "offline",
+            Self::Possible => "possible",
+            Self::Present => "present",
+        }
+    }
+
+    pub(super) fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|kind| kind.name() == name)
+    }
+
+    fn cpus(self) -> CpuSet {
+        match self {
+            Self::Online => hotplug::online_cpus(),
+            Self::Offline => {
+                let mut cpus = CpuSet::new_full();
+                for cpu in hotplug::online_cpus().iter() {
+                    cpus.remove(cpu);
+                }
+                cpus
+            }
+            Self::Possible | Self::Present => CpuSet::new_full(),
+        }
+    }
+}
+
+/// Represents the inodes at `/proc/sys/cpu/{online,offline,possible,present}`.
+pub(super) struct CpuListFileOps(CpuListKind);
+
+impl CpuListFileOps {
+    pub(super) fn new_inode(kind: CpuListKind, parent: Weak<dyn Inode>) -> Arc<dyn Inode> {
+        ProcFileBuilder::new(Self(kind))
+            .parent(parent)
+            .mode(InodeMode::from_bits_truncate(0o444))
+            .build()
+            .unwrap()
+    }
+}
+
+impl FileOps for CpuListFileOps {
+    fn data(&self) -> Result<Vec<u8>> {
+        let output = format!("{}\n", CpuList(&self.0.cpus()));
+        Ok(output.into_bytes())
+    }
+}
+
+/// Represents the inode at `/proc/sys/cpu/cpu<N>/online`.
+pub(super) struct CpuOnlineFileOps(CpuId);
+
+impl CpuOnlineFileOps {
+    pub(super) fn new_inode(cpu_id: CpuId, parent: Weak<dyn Inode>) -> Arc<dyn Inode> {
+        ProcFileBuilder::new(Self(cpu_id))
+            .parent(parent)
+            .mode(InodeMode::from_bits_truncate(0o644))
+            .build()
+            .unwrap()
+    }
+}
+
+impl FileOps for CpuOnlineFileOps {
+    fn data(&self) -> Result<Vec<u8>> {
+        let output = format!("{}\n", hotplug::is_online(self.0) as u32);
+        Ok(output.into_bytes())
+    }
+
+    fn write_data(&self, data: &[u8]) -> Result<()> {
+        let should_be_online = match core::str::from_utf8(data).map(str::trim) {
+            Ok("0") => false,
+            Ok("1") => true,
+            _ => return_errno_with_message!(Errno::EINVAL, "the value is invalid"),
+        };
+
+        // Like Linux, setting the current state again is not an error.
+        if hotplug::is_online(self.0) == should_be_online {
+            return Ok(());
+        }
+
+        if should_be_online {
+            hotplug::online_cpu(self.0)?;
+        } else {
+            hotplug::offline_cpu(self.0)?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,56 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! The `/proc/sys/kernel/kptr_restrict` file, which controls whether the kernel addresses are
+//! hidden from the users.
+//!
+//! See [`crate::util::kptr`] for the meaning of the values.
+
+use alloc::format;
+
+use crate::{
+    fs::{
+        procfs::template::{FileOps, ProcFileBuilder},
+        utils::{Inode, InodeMode},
+    },
+    prelude::*,
+    process::{credentials::capabilities::CapSet, posix_thread::AsPosixThread},
+    util::kptr,
+};
+
+/// Represents the inode at `/proc/sys/kernel/kptr_restrict`.
+pub struct KptrRestrictFileOps;
+
+impl KptrRestrictFileOps {
+    pub fn new_inode(parent: Weak<dyn Inode>) -> Arc<dyn Inode> {
+        ProcFileBuilder::new(Self)
+            .parent(parent)
+            .mode(InodeMode::from_bits_truncate(0o644))
+            .build()
+            .unwrap()
+    }
+}
+
+impl FileOps for KptrRestrictFileOps {
+    fn data(&self) -> Result<Vec<u8>> {
+        let output = format!("{}\n", kptr::kptr_restrict());
+        Ok(output.into_bytes())
+    }
+
+    fn write_data(&self, data: &[u8]) -> Result<()> {
+        // Like Linux, changing the policy requires `CAP_SYS_ADMIN`.
+        let is_privileged = {
+            let current = current_thread!();
+            let credentials = current.as_posix_thread().unwrap().credentials();
+            credentials.effective_capset().contains(CapSet::SYS_ADMIN)
+        };
+        if !is_privileged {
+            return_errno_with_message!(Errno::EPERM, "changing the policy requires CAP_SYS_ADMIN");
+        }
+
+        let value = core::str::from_utf8(data)
+            .ok()
+            .and_then(|data| data.trim().parse::<u8>().ok())
+            .ok_or_else(|| Error::with_message(Errno::EINVAL, "the value is invalid"))?;
+        kptr::set_kptr_restrict(value)
+    }
+}
@@ -0,0 +1,46 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! The `/proc/sys/kernel/log_filter` file, which controls what kernel messages are logged.
+//!
+//! The file contains the specification of the log filter, e.g., `info,aster_nix::fs=debug`.
+//! See [`aster_logger::filter`] for the syntax. Writing a specification replaces the filter.
+
+use alloc::format;
+
+use aster_logger::filter;
+
+use crate::{
+    fs::{
+        procfs::template::{FileOps, ProcFileBuilder},
+        utils::{Inode, InodeMode},
+    },
+    prelude::*,
+};
+
+/// Represents the inode at `/proc/sys/kernel/log_filter`.
+pub struct LogFilterFileOps;
+
+impl LogFilterFileOps {
+    pub fn new_inode(parent: Weak<dyn Inode>) -> Arc<dyn Inode> {
+        ProcFileBuilder::new(Self)
+            .parent(parent)
+            .mode(InodeMode::from_bits_truncate(0o644))
+            .build()
+            .unwrap()
+    }
+}
+
+impl FileOps for LogFilterFileOps {
+    fn data(&self) -> Result<Vec<u8>> {
+        let output = format!("{}\n", filter::spec());
+        Ok(output.into_bytes())
+    }
+
+    fn write_data(&self, data: &[u8]) -> Result<()> {
+        let Ok(spec) = core::str::from_utf8(data) else {
+            return_errno_with_message!(Errno::EINVAL, "the specification is not valid UTF-8");
+        };
+        filter::set_spec(spec)
+            .map_err(|_| Error::with_message(Errno::EINVAL, "the specification is invalid"))
+    }
+}
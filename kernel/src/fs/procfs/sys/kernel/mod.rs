@@ -3,7 +3,19 @@
 use crate::{
     fs::{
         procfs::{
-            sys::kernel::cap_last_cap::CapLastCapFileOps,
+            sys::kernel::{
+                cap_last_cap::CapLastCapFileOps,
+                core_pattern::CorePatternFileOps,
+                kptr_restrict::KptrRestrictFileOps,
+                log_filter::LogFilterFileOps,
+                panic_report::PanicReportFileOps,
+                printk::PrintkFileOps,
+                profiling::ProfilingDirOps,
+                random::RandomDirOps,
+                randomize_va_space::RandomizeVaSpaceFileOps,
+                tracing::TracingDirOps,
+                watchdog::{WatchdogFile, WatchdogFileOps},
+            },
             template::{DirOps, ProcDirBuilder},
             ProcDir,
         },
@@ -13,6 +25,16 @@ use crate::{
 };
 
 mod cap_last_cap;
+mod core_pattern;
+mod kptr_restrict;
+mod log_filter;
+mod panic_report;
+mod printk;
+mod profiling;
+mod random;
+mod randomize_va_space;
+mod tracing;
+mod watchdog;
 
 /// Represents the inode at `/proc/sys/kernel`.
 pub struct KernelDirOps;
@@ -27,7 +49,21 @@ impl DirOps for KernelDirOps {
     fn lookup_child(&self, this_ptr: Weak<dyn Inode>, name: &str) -> Result<Arc<dyn Inode>> {
         let inode = match name {
             "cap_last_cap" => CapLastCapFileOps::new_inode(this_ptr.clone()),
-            _ => return_errno!(Errno::ENOENT),
+            "core_pattern" => CorePatternFileOps::new_inode(this_ptr.clone()),
+            "kptr_restrict" => KptrRestrictFileOps::new_inode(this_ptr.clone()),
+            "log_filter" => LogFilterFileOps::new_inode(this_ptr.clone()),
+            "panic_report" => PanicReportFileOps::new_inode(this_ptr.clone()),
+            "printk" => PrintkFileOps::new_inode(this_ptr.clone()),
+            "profiling" => ProfilingDirOps::new_inode(this_ptr.clone()),
+            "random" => RandomDirOps::new_inode(this_ptr.clone()),
+            "randomize_va_space" => RandomizeVaSpaceFileOps::new_inode(this_ptr.clone()),
+            "tracing" => TracingDirOps::new_inode(this_ptr.clone()),
+            _ => {
+                let Some(file) = WatchdogFile::from_name(name) else {
+                    return_errno!(Errno::ENOENT);
+                };
+                WatchdogFileOps::new_inode(file, this_ptr.clone())
+            }
         };
         Ok(inode)
     }
@@ -41,5 +77,33 @@ impl DirOps for KernelDirOps {
         cached_children.put_entry_if_not_found("cap_last_cap", || {
             CapLastCapFileOps::new_inode(this_ptr.clone())
         });
+        cached_children.put_entry_if_not_found("core_pattern", || {
+            CorePatternFileOps::new_inode(this_ptr.clone())
+        });
+        cached_children.put_entry_if_not_found("kptr_restrict", || {
+            KptrRestrictFileOps::new_inode(this_ptr.clone())
+        });
+        cached_children.put_entry_if_not_found("log_filter", || {
+            LogFilterFileOps::new_inode(this_ptr.clone())
+        });
+        cached_children.put_entry_if_not_found("panic_report", || {
+            PanicReportFileOps::new_inode(this_ptr.clone())
+        });
+        cached_children
+            .put_entry_if_not_found("printk", || PrintkFileOps::new_inode(this_ptr.clone()));
+        cached_children
+            .put_entry_if_not_found("profiling", || ProfilingDirOps::new_inode(this_ptr.clone()));
+        cached_children
+            .put_entry_if_not_found("random", || RandomDirOps::new_inode(this_ptr.clone()));
+        cached_children.put_entry_if_not_found("randomize_va_space", || {
+            RandomizeVaSpaceFileOps::new_inode(this_ptr.clone())
+        });
+        cached_children
+            .put_entry_if_not_found("tracing", || TracingDirOps::new_inode(this_ptr.clone()));
+        for file in WatchdogFile::ALL {
+            cached_children.put_entry_if_not_found(file.name(), || {
+                WatchdogFileOps::new_inode(file, this_ptr.clone())
+            });
+        }
     }
 }
@@ -0,0 +1,43 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! This module offers the file that reads the panic report saved in the previous boot.
+//!
+//! The report is saved to the region reserved with the `ostd.pstore=SIZE@ADDR` kernel command
+//! line argument when the kernel panics. Reading the file returns the report, or nothing if
+//! there is no report. Writing anything erases the report.
+//!
+//! The file corresponds to `/sys/fs/pstore/dmesg-ramoops-0` in Linux.
+
+use ostd::panic::pstore::{erase_previous_panic_report, previous_panic_report};
+
+use crate::{
+    fs::{
+        procfs::template::{FileOps, ProcFileBuilder},
+        utils::{Inode, InodeMode},
+    },
+    prelude::*,
+};
+
+/// Represents the inode at `/proc/sys/kernel/panic_report`.
+pub struct PanicReportFileOps;
+
+impl PanicReportFileOps {
+    pub fn new_inode(parent: Weak<dyn Inode>) -> Arc<dyn Inode> {
+        ProcFileBuilder::new(Self)
+            .parent(parent)
+            .mode(InodeMode::from_bits_truncate(0o600))
+            .build()
+            .unwrap()
+    }
+}
+
+impl FileOps for PanicReportFileOps {
+    fn data(&self) -> Result<Vec<u8>> {
+        Ok(previous_panic_report().unwrap_or_default())
+    }
+
+    fn write_data(&self, _data: &[u8]) -> Result<()> {
+        erase_previous_panic_report();
+        Ok(())
+    }
+}
@@ -0,0 +1,61 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! The `/proc/sys/kernel/printk` file, which controls the console log level.
+//!
+//! The file contains four values like Linux: the console log level, the default message log
+//! level, the minimum console log level, and the default console log level. Only the first one
+//! can be changed.
+
+use alloc::format;
+
+use aster_logger::MIN_CONSOLE_LOGLEVEL;
+
+use crate::{
+    fs::{
+        procfs::template::{FileOps, ProcFileBuilder},
+        utils::{Inode, InodeMode},
+    },
+    prelude::*,
+};
+
+/// The default message log level, which is the same as Linux.
+const DEFAULT_MESSAGE_LOGLEVEL: u8 = 4;
+
+/// The default console log level, which is the same as Linux.
+const DEFAULT_CONSOLE_LOGLEVEL: u8 = 7;
+
+/// Represents the inode at `/proc/sys/kernel/printk`.
+pub struct PrintkFileOps;
+
+impl PrintkFileOps {
+    pub fn new_inode(parent: Weak<dyn Inode>) -> Arc<dyn Inode> {
+        ProcFileBuilder::new(Self)
+            .parent(parent)
+            .mode(InodeMode::from_bits_truncate(0o644))
+            .build()
+            .unwrap()
+    }
+}
+
+impl FileOps for PrintkFileOps {
+    fn data(&self) -> Result<Vec<u8>> {
+        let output = format!(
+            "{}\t{}\t{}\t{}\n",
+            aster_logger::console_loglevel(),
+            DEFAULT_MESSAGE_LOGLEVEL,
+            MIN_CONSOLE_LOGLEVEL,
+            DEFAULT_CONSOLE_LOGLEVEL
+        );
+        Ok(output.into_bytes())
+    }
+
+    fn write_data(&self, data: &[u8]) -> Result<()> {
+        let level = core::str::from_utf8(data)
+            .ok()
+            .and_then(|data| data.split_whitespace().next())
+            .and_then(|level| level.parse::<u8>().ok())
+            .ok_or_else(|| Error::with_message(Errno::EINVAL, "the value is invalid"))?;
+        aster_logger::set_console_loglevel(level);
+        Ok(())
+    }
+}
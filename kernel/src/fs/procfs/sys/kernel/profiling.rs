@@ -0,0 +1,112 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! This module offers the files that control the sampling profiler and read its samples.
+//!
+//! The files are placed in `/proc/sys/kernel/profiling`:
+//! - `profiling_on`: Reads or writes `0` or `1` to turn the profiler off or on.
+//! - `samples`: Reads the samples, one per line in the format of
+//!   `<cpu> <tid> <kernel|user> <callchain>`. Writing anything clears the samples. The kernel
+//!   addresses in the callchains are hidden according to `kptr_restrict`.
+
+use alloc::format;
+
+use crate::{
+    fs::{
+        procfs::{
+            template::{DirOps, FileOps, ProcDirBuilder, ProcFileBuilder},
+            ProcDir,
+        },
+        utils::{DirEntryVecExt, Inode, InodeMode},
+    },
+    prelude::*,
+    thread::profiler,
+};
+
+/// Represents the inode at `/proc/sys/kernel/profiling`.
+pub struct ProfilingDirOps;
+
+impl ProfilingDirOps {
+    pub fn new_inode(parent: Weak<dyn Inode>) -> Arc<dyn Inode> {
+        ProcDirBuilder::new(Self).parent(parent).build().unwrap()
+    }
+}
+
+impl DirOps for ProfilingDirOps {
+    fn lookup_child(&self, this_ptr: Weak<dyn Inode>, name: &str) -> Result<Arc<dyn Inode>> {
+        let inode = match name {
+            "profiling_on" => ProfilingOnFileOps::new_inode(this_ptr),
+            "samples" => SamplesFileOps::new_inode(this_ptr),
+            _ => return_errno!(Errno::ENOENT),
+        };
+        Ok(inode)
+    }
+
+    fn populate_children(&self, this_ptr: Weak<dyn Inode>) {
+        let this = {
+            let this = this_ptr.upgrade().unwrap();
+            this.downcast_ref::<ProcDir<ProfilingDirOps>>()
+                .unwrap()
+                .this()
+        };
+        let mut cached_children = this.cached_children().write();
+        cached_children.put_entry_if_not_found("profiling_on", || {
+            ProfilingOnFileOps::new_inode(this_ptr.clone())
+        });
+        cached_children
+            .put_entry_if_not_found("samples", || SamplesFileOps::new_inode(this_ptr.clone()));
+    }
+}
+
+/// Represents the inode at `/proc/sys/kernel/profiling/profiling_on`.
+struct ProfilingOnFileOps;
+
+impl ProfilingOnFileOps {
+    fn new_inode(parent: Weak<dyn Inode>) -> Arc<dyn Inode> {
+        ProcFileBuilder::new(Self)
+            .parent(parent)
+            .mode(InodeMode::from_bits_truncate(0o644))
+            .build()
+            .unwrap()
+    }
+}
+
+impl FileOps for ProfilingOnFileOps {
+    fn data(&self) -> Result<Vec<u8>> {
+        let output = format!("{}\n", profiler::is_profiling_on() as u32);
+        Ok(output.into_bytes())
+    }
+
+    fn write_data(&self, data: &[u8]) -> Result<()> {
+        let is_on = match core::str::from_utf8(data).map(str::trim) {
+            Ok("0") => false,
+            Ok("1") => true,
+            _ => return_errno_with_message!(Errno::EINVAL, "the value is invalid"),
+        };
+        profiler::set_profiling_on(is_on);
+        Ok(())
+    }
+}
+
+/// Represents the inode at `/proc/sys/kernel/profiling/samples`.
+struct SamplesFileOps;
+
+impl SamplesFileOps {
+    fn new_inode(parent: Weak<dyn Inode>) -> Arc<dyn Inode> {
+        ProcFileBuilder::new(Self)
+            .parent(parent)
+            .mode(InodeMode::from_bits_truncate(0o644))
+            .build()
+            .unwrap()
+    }
+}
+
+impl FileOps for SamplesFileOps {
+    fn data(&self) -> Result<Vec<u8>> {
+        Ok(profiler::read_samples().into_bytes())
+    }
+
+    fn write_data(&self, _data: &[u8]) -> Result<()> {
+        profiler::clear();
+        Ok(())
+    }
+}
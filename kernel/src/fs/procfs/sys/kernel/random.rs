@@ -0,0 +1,130 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! This module offers the files that report the state of the kernel random number generator.
+//!
+//! The files are placed in `/proc/sys/kernel/random`:
+//! - `entropy_avail`: The entropy credited to the input pool in bits.
+//! - `poolsize`: The capacity of the input pool in bits.
+//! - `boot_id`: A random UUID that is generated once per boot.
+//! - `uuid`: A random UUID that is generated on each read.
+
+use alloc::format;
+
+use spin::Once;
+
+use crate::{
+    fs::{
+        procfs::{
+            template::{DirOps, FileOps, ProcDirBuilder, ProcFileBuilder},
+            ProcDir,
+        },
+        utils::{DirEntryVecExt, Inode},
+    },
+    prelude::*,
+    util::random,
+};
+
+/// Represents the inode at `/proc/sys/kernel/random`.
+pub struct RandomDirOps;
+
+impl RandomDirOps {
+    pub fn new_inode(parent: Weak<dyn Inode>) -> Arc<dyn Inode> {
+        ProcDirBuilder::new(Self).parent(parent).build().unwrap()
+    }
+}
+
+impl DirOps for RandomDirOps {
+    fn lookup_child(&self, this_ptr: Weak<dyn Inode>, name: &str) -> Result<Arc<dyn Inode>> {
+        let Some(file) = RandomFile::from_name(name) else {
+            return_errno!(Errno::ENOENT);
+        };
+        Ok(RandomFileOps::new_inode(file, this_ptr))
+    }
+
+    fn populate_children(&self, this_ptr: Weak<dyn Inode>) {
+        let this = {
+            let this = this_ptr.upgrade().unwrap();
+            this.downcast_ref::<ProcDir<RandomDirOps>>().unwrap().this()
+        };
+        let mut cached_children = this.cached_children().write();
+        for file in RandomFile::ALL {
+            cached_children.put_entry_if_not_found(file.name(), || {
+                RandomFileOps::new_inode(file, this_ptr.clone())
+            });
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+enum RandomFile {
+    EntropyAvail,
+    PoolSize,
+    BootId,
+    Uuid,
+}
+
+impl RandomFile {
+    const ALL: [Self; 4] = [Self::EntropyAvail, Self::PoolSize, Self::BootId, Self::Uuid];
+
+    fn name(self) -> &'static str {
+        match self {
+            Self::EntropyAvail => "entropy_avail",
+            Self::PoolSize => "poolsize",
+            Self::BootId => "boot_id",
+            Self::Uuid => "uuid",
+        }
+    }
+
+    fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|file| file.name() == name)
+    }
+}
+
+/// Represents the inodes in `/proc/sys/kernel/random`.
+struct RandomFileOps(RandomFile);
+
+impl RandomFileOps {
+    fn new_inode(file: RandomFile, parent: Weak<dyn Inode>) -> Arc<dyn Inode> {
+        ProcFileBuilder::new(Self(file))
+            .parent(parent)
+            .build()
+            .unwrap()
+    }
+}
+
+impl FileOps for RandomFileOps {
+    fn data(&self) -> Result<Vec<u8>> {
+        static BOOT_ID: Once<String> = Once::new();
+
+        let output = match self.0 {
+            RandomFile::EntropyAvail => format!("{}\n", random::entropy_avail()),
+            RandomFile::PoolSize => format!("{}\n", random::POOL_BITS),
+            RandomFile::BootId => format!("{}\n", BOOT_ID.call_once(generate_uuid)),
+            RandomFile::Uuid => format!("{}\n", generate_uuid()),
+        };
+        Ok(output.into_bytes())
+    }
+}
+
+/// Generates a random (version 4) UUID.
+fn generate_uuid() -> String {
+    let mut bytes = [0u8; 16];
+    random::getrandom(&mut bytes);
+    bytes[6] = (bytes[6] & 0x0f) | 0x40;
+    bytes[8] = (bytes[8] & 0x3f) | 0x80;
+
+    let hex = |range: core::ops::Range<usize>| {
+        bytes[range]
+            .iter()
+            .map(|byte| format!("{:02x}", byte))
+            .collect::<String>()
+    };
+    format!(
+        "{}-{}-{}-{}-{}",
+        hex(0..4),
+        hex(4..6),
+        hex(6..8),
+        hex(8..10),
+        hex(10..16)
+    )
+}
@@ -0,0 +1,54 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! The `/proc/sys/kernel/randomize_va_space` file, which controls the user address space layout
+//! randomization.
+//!
+//! See [`crate::process::aslr`] for the meaning of the values.
+
+use alloc::format;
+
+use crate::{
+    fs::{
+        procfs::template::{FileOps, ProcFileBuilder},
+        utils::{Inode, InodeMode},
+    },
+    prelude::*,
+    process::{aslr, credentials::capabilities::CapSet, posix_thread::AsPosixThread},
+};
+
+/// Represents the inode at `/proc/sys/kernel/randomize_va_space`.
+pub struct RandomizeVaSpaceFileOps;
+
+impl RandomizeVaSpaceFileOps {
+    pub fn new_inode(parent: Weak<dyn Inode>) -> Arc<dyn Inode> {
+        ProcFileBuilder::new(Self)
+            .parent(parent)
+            .mode(InodeMode::from_bits_truncate(0o644))
+            .build()
+            .unwrap()
+    }
+}
+
+impl FileOps for RandomizeVaSpaceFileOps {
+    fn data(&self) -> Result<Vec<u8>> {
+        let output = format!("{}\n", aslr::randomize_va_space());
+        Ok(output.into_bytes())
+    }
+
+    fn write_data(&self, data: &[u8]) -> Result<()> {
+        let is_privileged = {
+            let current = current_thread!();
+            let credentials = current.as_posix_thread().unwrap().credentials();
+            credentials.effective_capset().contains(CapSet::SYS_ADMIN)
+        };
+        if !is_privileged {
+            return_errno_with_message!(Errno::EPERM, "changing the value requires CAP_SYS_ADMIN");
+        }
+
+        let value = core::str::from_utf8(data)
+            .ok()
+            .and_then(|data| data.trim().parse::<u8>().ok())
+            .ok_or_else(|| Error::with_message(Errno::EINVAL, "the value is invalid"))?;
+        aslr::set_randomize_va_space(value)
+    }
+}
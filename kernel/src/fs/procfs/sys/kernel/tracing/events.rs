@@ -0,0 +1,158 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! This module offers the files that enable or disable the tracepoints.
+//!
+//! Each tracepoint has a directory at `events/<subsystem>/<name>`, which contains an `enable`
+//! file.
+
+use alloc::format;
+
+use aster_trace::{events::ALL_TRACEPOINTS, Tracepoint};
+
+use super::parse_bool;
+use crate::{
+    fs::{
+        procfs::{
+            template::{DirOps, FileOps, ProcDirBuilder, ProcFileBuilder},
+            ProcDir,
+        },
+        utils::{DirEntryVecExt, Inode, InodeMode},
+    },
+    prelude::*,
+};
+
+/// Represents the inode at `/proc/sys/kernel/tracing/events`.
+pub(super) struct EventsDirOps;
+
+impl EventsDirOps {
+    pub(super) fn new_inode(parent: Weak<dyn Inode>) -> Arc<dyn Inode> {
+        ProcDirBuilder::new(Self).parent(parent).build().unwrap()
+    }
+}
+
+impl DirOps for EventsDirOps {
+    fn lookup_child(&self, this_ptr: Weak<dyn Inode>, name: &str) -> Result<Arc<dyn Inode>> {
+        let Some(tracepoint) = ALL_TRACEPOINTS
+            .iter()
+            .find(|tracepoint| tracepoint.subsystem() == name)
+        else {
+            return_errno!(Errno::ENOENT);
+        };
+        Ok(SubsystemDirOps::new_inode(tracepoint.subsystem(), this_ptr))
+    }
+
+    fn populate_children(&self, this_ptr: Weak<dyn Inode>) {
+        let this = {
+            let this = this_ptr.upgrade().unwrap();
+            this.downcast_ref::<ProcDir<EventsDirOps>>().unwrap().this()
+        };
+        let mut cached_children = this.cached_children().write();
+        for tracepoint in ALL_TRACEPOINTS {
+            cached_children.put_entry_if_not_found(tracepoint.subsystem(), || {
+                SubsystemDirOps::new_inode(tracepoint.subsystem(), this_ptr.clone())
+            });
+        }
+    }
+}
+
+/// Represents the inodes at `/proc/sys/kernel/tracing/events/<subsystem>`.
+struct SubsystemDirOps(&'static str);
+
+impl SubsystemDirOps {
+    fn new_inode(subsystem: &'static str, parent: Weak<dyn Inode>) -> Arc<dyn Inode> {
+        ProcDirBuilder::new(Self(subsystem))
+            .parent(parent)
+            .build()
+            .unwrap()
+    }
+
+    fn tracepoints(&self) -> impl Iterator<Item = &'static Tracepoint> + '_ {
+        ALL_TRACEPOINTS
+            .into_iter()
+            .filter(|tracepoint| tracepoint.subsystem() == self.0)
+    }
+}
+
+impl DirOps for SubsystemDirOps {
+    fn lookup_child(&self, this_ptr: Weak<dyn Inode>, name: &str) -> Result<Arc<dyn Inode>> {
+        let Some(tracepoint) = self
+            .tracepoints()
+            .find(|tracepoint| tracepoint.name() == name)
+        else {
+            return_errno!(Errno::ENOENT);
+        };
+        Ok(EventDirOps::new_inode(tracepoint, this_ptr))
+    }
+
+    fn populate_children(&self, this_ptr: Weak<dyn Inode>) {
+        let this = {
+            let this = this_ptr.upgrade().unwrap();
+            this.downcast_ref::<ProcDir<SubsystemDirOps>>()
+                .unwrap()
+                .this()
+        };
+        let mut cached_children = this.cached_children().write();
+        for tracepoint in self.tracepoints() {
+            cached_children.put_entry_if_not_found(tracepoint.name(), || {
+                EventDirOps::new_inode(tracepoint, this_ptr.clone())
+            });
+        }
+    }
+}
+
+/// Represents the inodes at `/proc/sys/kernel/tracing/events/<subsystem>/<name>`.
+struct EventDirOps(&'static Tracepoint);
+
+impl EventDirOps {
+    fn new_inode(tracepoint: &'static Tracepoint, parent: Weak<dyn Inode>) -> Arc<dyn Inode> {
+        ProcDirBuilder::new(Self(tracepoint))
+            .parent(parent)
+            .build()
+            .unwrap()
+    }
+}
+
+impl DirOps for EventDirOps {
+    fn lookup_child(&self, this_ptr: Weak<dyn Inode>, name: &str) -> Result<Arc<dyn Inode>> {
+        if name != "enable" {
+            return_errno!(Errno::ENOENT);
+        }
+        Ok(EnableFileOps::new_inode(self.0, this_ptr))
+    }
+
+    fn populate_children(&self, this_ptr: Weak<dyn Inode>) {
+        let this = {
+            let this = this_ptr.upgrade().unwrap();
+            this.downcast_ref::<ProcDir<EventDirOps>>().unwrap().this()
+        };
+        let mut cached_children = this.cached_children().write();
+        cached_children.put_entry_if_not_found("enable", || {
+            EnableFileOps::new_inode(self.0, this_ptr.clone())
+        });
+    }
+}
+
+/// Represents the inodes at `/proc/sys/kernel/tracing/events/<subsystem>/<name>/enable`.
+struct EnableFileOps(&'static Tracepoint);
+
+impl EnableFileOps {
+    fn new_inode(tracepoint: &'static Tracepoint, parent: Weak<dyn Inode>) -> Arc<dyn Inode> {
+        ProcFileBuilder::new(Self(tracepoint))
+            .parent(parent)
+            .mode(InodeMode::from_bits_truncate(0o644))
+            .build()
+            .unwrap()
+    }
+}
+
+impl FileOps for EnableFileOps {
+    fn data(&self) -> Result<Vec<u8>> {
+        let output = format!("{}\n", self.0.is_enabled() as u32);
+        Ok(output.into_bytes())
+    }
+
+    fn write_data(&self, data: &[u8]) -> Result<()> {
+        self.0.set_enabled(parse_bool(data)?);
+        Ok(())
+    }
+}
@@ -0,0 +1,91 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! This module offers the file that adds or removes the kprobes at kernel functions.
+//!
+//! Each line written to `kprobe_events` is a command:
+//! - `p <addr>`: Adds a kprobe at the function at the address.
+//! - `- <addr>`: Removes the kprobe at the address.
+//!
+//! The address is in hexadecimal, and can be found in the symbol table of the kernel. Like the
+//! symbol table, the addresses written to and read from the file do not include the offset of
+//! the kernel image chosen by KASLR. Each hit of a kprobe is recorded as an event at the
+//! `kprobes/kprobe` tracepoint, which should be enabled to record the events. Reading the file
+//! lists the addresses of the kprobes, which are hidden according to `kptr_restrict`.
+//!
+//! Unlike Linux, the kprobes are not named and have no fetch arguments.
+
+use alloc::format;
+
+use aster_trace::{events::KPROBE, trace_event};
+use ostd::{
+    arch::kprobe::{kprobe_addrs, register_kprobe, unregister_kprobe},
+    mm::kaslr_offset,
+    trap::TrapFrame,
+};
+
+use crate::{
+    fs::{
+        procfs::template::{FileOps, ProcFileBuilder},
+        utils::{Inode, InodeMode},
+    },
+    prelude::*,
+    util::kptr::KernelPtr,
+};
+
+/// Represents the inode at `/proc/sys/kernel/tracing/kprobe_events`.
+pub(super) struct KprobeEventsFileOps;
+
+impl KprobeEventsFileOps {
+    pub(super) fn new_inode(parent: Weak<dyn Inode>) -> Arc<dyn Inode> {
+        ProcFileBuilder::new(Self)
+            .parent(parent)
+            .mode(InodeMode::from_bits_truncate(0o644))
+            .build()
+            .unwrap()
+    }
+}
+
+impl FileOps for KprobeEventsFileOps {
+    fn data(&self) -> Result<Vec<u8>> {
+        let output: String = kprobe_addrs()
+            .into_iter()
+            .map(|addr| format!("p {}\n", KernelPtr(addr - kaslr_offset())))
+            .collect();
+        Ok(output.into_bytes())
+    }
+
+    fn write_data(&self, data: &[u8]) -> Result<()> {
+        let Ok(commands) = core::str::from_utf8(data) else {
+            return_errno_with_message!(Errno::EINVAL, "the commands are not valid UTF-8");
+        };
+
+        for command in commands.lines().map(str::trim) {
+            if command.is_empty() {
+                continue;
+            }
+            let Some((op, addr)) = command.split_once(char::is_whitespace) else {
+                return_errno_with_message!(Errno::EINVAL, "the command is invalid");
+            };
+            let addr = addr.trim();
+            let addr = addr.strip_prefix("0x").unwrap_or(addr);
+            let Ok(addr) = Vaddr::from_str_radix(addr, 16) else {
+                return_errno_with_message!(Errno::EINVAL, "the address is invalid");
+            };
+
+            let Some(addr) = addr.checked_add(kaslr_offset()) else {
+                return_errno_with_message!(Errno::EINVAL, "the address is invalid");
+            };
+
+            match op {
+                "p" => register_kprobe(addr, trace_kprobe)?,
+                "-" => unregister_kprobe(addr)?,
+                _ => return_errno_with_message!(Errno::EINVAL, "the command is unknown"),
+            }
+        }
+        Ok(())
+    }
+}
+
+fn trace_kprobe(f: &TrapFrame) {
+    trace_event!(KPROBE, f.rip, f.rdi, f.rsi, f.rdx);
+}
@@ -0,0 +1,167 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! This module offers the files that control kernel tracing and read the recorded events.
+//!
+//! The files are placed in `/proc/sys/kernel/tracing`, while they are in `/sys/kernel/tracing`
+//! on Linux.
+//!
+//! Reference: <https://docs.kernel.org/trace/ftrace.html>
+
+use alloc::format;
+
+use aster_trace::events::ALL_TRACEPOINTS;
+
+use self::events::EventsDirOps;
+use crate::{
+    fs::{
+        procfs::{
+            template::{DirOps, FileOps, ProcDirBuilder, ProcFileBuilder},
+            ProcDir,
+        },
+        utils::{DirEntryVecExt, Inode, InodeMode},
+    },
+    prelude::*,
+};
+
+mod events;
+#[cfg(target_arch = "x86_64")]
+mod kprobe_events;
+
+/// Represents the inode at `/proc/sys/kernel/tracing`.
+pub struct TracingDirOps;
+
+impl TracingDirOps {
+    pub fn new_inode(parent: Weak<dyn Inode>) -> Arc<dyn Inode> {
+        ProcDirBuilder::new(Self).parent(parent).build().unwrap()
+    }
+}
+
+impl DirOps for TracingDirOps {
+    fn lookup_child(&self, this_ptr: Weak<dyn Inode>, name: &str) -> Result<Arc<dyn Inode>> {
+        if name == "events" {
+            return Ok(EventsDirOps::new_inode(this_ptr));
+        }
+        #[cfg(target_arch = "x86_64")]
+        if name == "kprobe_events" {
+            return Ok(kprobe_events::KprobeEventsFileOps::new_inode(this_ptr));
+        }
+
+        let Some(file) = TracingFile::from_name(name) else {
+            return_errno!(Errno::ENOENT);
+        };
+        Ok(TracingFileOps::new_inode(file, this_ptr))
+    }
+
+    fn populate_children(&self, this_ptr: Weak<dyn Inode>) {
+        let this = {
+            let this = this_ptr.upgrade().unwrap();
+            this.downcast_ref::<ProcDir<TracingDirOps>>()
+                .unwrap()
+                .this()
+        };
+        let mut cached_children = this.cached_children().write();
+        cached_children
+            .put_entry_if_not_found("events", || EventsDirOps::new_inode(this_ptr.clone()));
+        #[cfg(target_arch = "x86_64")]
+        cached_children.put_entry_if_not_found("kprobe_events", || {
+            kprobe_events::KprobeEventsFileOps::new_inode(this_ptr.clone())
+        });
+        for file in TracingFile::ALL {
+            cached_children.put_entry_if_not_found(file.name(), || {
+                TracingFileOps::new_inode(file, this_ptr.clone())
+            });
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+enum TracingFile {
+    TracingOn,
+    BufferSizeKb,
+    Trace,
+    AvailableEvents,
+}
+
+impl TracingFile {
+    const ALL: [Self; 4] = [
+        Self::TracingOn,
+        Self::BufferSizeKb,
+        Self::Trace,
+        Self::AvailableEvents,
+    ];
+
+    fn name(self) -> &'static str {
+        match self {
+            Self::TracingOn => "tracing_on",
+            Self::BufferSizeKb => "buffer_size_kb",
+            Self::Trace => "trace",
+            Self::AvailableEvents => "available_events",
+        }
+    }
+
+    fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|file| file.name() == name)
+    }
+
+    fn is_writable(self) -> bool {
+        !matches!(self, Self::AvailableEvents)
+    }
+}
+
+/// Represents the inodes at `/proc/sys/kernel/tracing/*`.
+struct TracingFileOps(TracingFile);
+
+impl TracingFileOps {
+    fn new_inode(file: TracingFile, parent: Weak<dyn Inode>) -> Arc<dyn Inode> {
+        let mode = if file.is_writable() { 0o644 } else { 0o444 };
+        ProcFileBuilder::new(Self(file))
+            .parent(parent)
+            .mode(InodeMode::from_bits_truncate(mode))
+            .build()
+            .unwrap()
+    }
+}
+
+impl FileOps for TracingFileOps {
+    fn data(&self) -> Result<Vec<u8>> {
+        let output = match self.0 {
+            TracingFile::TracingOn => format!("{}\n", aster_trace::is_tracing_on() as u32),
+            TracingFile::BufferSizeKb => format!("{}\n", aster_trace::buffer_size_kb()),
+            TracingFile::Trace => aster_trace::read_trace(),
+            TracingFile::AvailableEvents => ALL_TRACEPOINTS
+                .iter()
+                .map(|tracepoint| format!("{}:{}\n", tracepoint.subsystem(), tracepoint.name()))
+                .collect(),
+        };
+        Ok(output.into_bytes())
+    }
+
+    fn write_data(&self, data: &[u8]) -> Result<()> {
+        match self.0 {
+            TracingFile::TracingOn => aster_trace::set_tracing_on(parse_bool(data)?),
+            TracingFile::BufferSizeKb => {
+                let size_kb = core::str::from_utf8(data)
+                    .ok()
+                    .and_then(|data| data.trim().parse::<usize>().ok())
+                    .ok_or_else(|| Error::with_message(Errno::EINVAL, "the value is invalid"))?;
+                aster_trace::set_buffer_size_kb(size_kb);
+            }
+            // Like Linux, writing anything (usually by opening the file with `O_TRUNC`) clears
+            // the recorded events.
+            TracingFile::Trace => aster_trace::clear(),
+            TracingFile::AvailableEvents => {
+                return_errno_with_message!(Errno::EPERM, "the file is read-only")
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Parses `0` or `1` written to a file.
+fn parse_bool(data: &[u8]) -> Result<bool> {
+    match core::str::from_utf8(data).map(str::trim) {
+        Ok("0") => Ok(false),
+        Ok("1") => Ok(true),
+        _ => return_errno_with_message!(Errno::EINVAL, "the value is invalid"),
+    }
+}
@@ -0,0 +1,117 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! This module offers the files that control the soft-lockup and hung-task detectors.
+//!
+//! Reference: <https://docs.kernel.org/admin-guide/sysctl/kernel.html>
+
+use alloc::format;
+
+use ostd::task::watchdog;
+
+use crate::{
+    fs::{
+        procfs::template::{FileOps, ProcFileBuilder},
+        utils::{Inode, InodeMode},
+    },
+    prelude::*,
+    thread::hung_task,
+};
+
+/// The maximum value of `watchdog_thresh`, which is the same as Linux.
+const MAX_WATCHDOG_THRESH: u64 = 60;
+
+#[derive(Debug, Clone, Copy)]
+pub(super) enum WatchdogFile {
+    WatchdogThresh,
+    SoftlockupPanic,
+    HungTaskTimeoutSecs,
+    HungTaskPanic,
+    HungTaskWarnings,
+}
+
+impl WatchdogFile {
+    pub(super) const ALL: [Self; 5] = [
+        Self::WatchdogThresh,
+        Self::SoftlockupPanic,
+        Self::HungTaskTimeoutSecs,
+        Self::HungTaskPanic,
+        Self::HungTaskWarnings,
+    ];
+
+    pub(super) fn name(self) -> &'static str {
+        match self {
+            Self::WatchdogThresh => "watchdog_thresh",
+            Self::SoftlockupPanic => "softlockup_panic",
+            Self::HungTaskTimeoutSecs => "hung_task_timeout_secs",
+            Self::HungTaskPanic => "hung_task_panic",
+            Self::HungTaskWarnings => "hung_task_warnings",
+        }
+    }
+
+    pub(super) fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|file| file.name() == name)
+    }
+}
+
+/// Represents the inodes at `/proc/sys/kernel/{watchdog_thresh,softlockup_panic,hung_task_*}`.
+pub struct WatchdogFileOps(WatchdogFile);
+
+impl WatchdogFileOps {
+    pub(super) fn new_inode(file: WatchdogFile, parent: Weak<dyn Inode>) -> Arc<dyn Inode> {
+        ProcFileBuilder::new(Self(file))
+            .parent(parent)
+            .mode(InodeMode::from_bits_truncate(0o644))
+            .build()
+            .unwrap()
+    }
+}
+
+impl FileOps for WatchdogFileOps {
+    fn data(&self) -> Result<Vec<u8>> {
+        let output = match self.0 {
+            // A soft lockup is reported after twice the threshold, which is the same as Linux.
+            WatchdogFile::WatchdogThresh => format!("{}\n", watchdog::threshold_secs() / 2),
+            WatchdogFile::SoftlockupPanic => format!("{}\n", watchdog::panic_on_lockup() as u32),
+            WatchdogFile::HungTaskTimeoutSecs => format!("{}\n", hung_task::timeout_secs()),
+            WatchdogFile::HungTaskPanic => {
+                format!("{}\n", hung_task::panic_on_hung_task() as u32)
+            }
+            WatchdogFile::HungTaskWarnings => format!("{}\n", hung_task::warnings()),
+        };
+        Ok(output.into_bytes())
+    }
+
+    fn write_data(&self, data: &[u8]) -> Result<()> {
+        let value = core::str::from_utf8(data)
+            .ok()
+            .and_then(|data| data.trim().parse::<i64>().ok())
+            .ok_or_else(|| Error::with_message(Errno::EINVAL, "the value is invalid"))?;
+
+        match self.0 {
+            WatchdogFile::WatchdogThresh => {
+                let thresh = u64::try_from(value)
+                    .ok()
+                    .filter(|thresh| *thresh <= MAX_WATCHDOG_THRESH)
+                    .ok_or_else(|| Error::with_message(Errno::EINVAL, "the value is invalid"))?;
+                watchdog::set_threshold_secs(thresh * 2);
+            }
+            WatchdogFile::SoftlockupPanic => watchdog::set_panic_on_lockup(parse_bool(value)?),
+            WatchdogFile::HungTaskTimeoutSecs => {
+                let secs = u64::try_from(value)
+                    .map_err(|_| Error::with_message(Errno::EINVAL, "the value is invalid"))?;
+                hung_task::set_timeout_secs(secs);
+            }
+            WatchdogFile::HungTaskPanic => hung_task::set_panic_on_hung_task(parse_bool(value)?),
+            WatchdogFile::HungTaskWarnings => hung_task::set_warnings(value)?,
+        }
+        Ok(())
+    }
+}
+
+fn parse_bool(value: i64) -> Result<bool> {
+    match value {
+        0 => Ok(false),
+        1 => Ok(true),
+        _ => return_errno_with_message!(Errno::EINVAL, "the value must be 0 or 1"),
+    }
+}
@@ -1,6 +1,6 @@
 // SPDX-License-Identifier: MPL-2.0
 
-use self::kernel::KernelDirOps;
+use self::{cpu::CpuDirOps, kernel::KernelDirOps, vm::VmDirOps};
 use crate::{
     fs::{
         procfs::template::{DirOps, ProcDir, ProcDirBuilder},
@@ -9,7 +9,9 @@ use crate::{
     prelude::*,
 };
 
+mod cpu;
 mod kernel;
+mod vm;
 
 /// Represents the inode at `/proc/sys`.
 pub struct SysDirOps;
@@ -23,7 +25,9 @@ impl SysDirOps {
 impl DirOps for SysDirOps {
     fn lookup_child(&self, this_ptr: Weak<dyn Inode>, name: &str) -> Result<Arc<dyn Inode>> {
         let inode = match name {
+            "cpu" => CpuDirOps::new_inode(this_ptr.clone()),
             "kernel" => KernelDirOps::new_inode(this_ptr.clone()),
+            "vm" => VmDirOps::new_inode(this_ptr.clone()),
             _ => return_errno!(Errno::ENOENT),
         };
         Ok(inode)
@@ -35,7 +39,9 @@ impl DirOps for SysDirOps {
             this.downcast_ref::<ProcDir<SysDirOps>>().unwrap().this()
         };
         let mut cached_children = this.cached_children().write();
+        cached_children.put_entry_if_not_found("cpu", || CpuDirOps::new_inode(this_ptr.clone()));
         cached_children
-            .put_entry_if_not_found("kernel", || KernelDirOps::new_inode(this_ptr.clone()))
+            .put_entry_if_not_found("kernel", || KernelDirOps::new_inode(this_ptr.clone()));
+        cached_children.put_entry_if_not_found("vm", || VmDirOps::new_inode(this_ptr.clone()));
     }
 }
@@ -0,0 +1,145 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! This module offers the files that control kernel same-page merging (KSM).
+//!
+//! The files are placed in `/proc/sys/vm/ksm`, while they are in `/sys/kernel/mm/ksm` on Linux.
+//!
+//! Reference: <https://docs.kernel.org/admin-guide/mm/ksm.html>
+
+use alloc::format;
+
+use crate::{
+    fs::{
+        procfs::{
+            template::{DirOps, FileOps, ProcDirBuilder, ProcFileBuilder},
+            ProcDir,
+        },
+        utils::{DirEntryVecExt, Inode, InodeMode},
+    },
+    prelude::*,
+    vm::ksm::{self, KsmRun},
+};
+
+/// Represents the inode at `/proc/sys/vm/ksm`.
+pub struct KsmDirOps;
+
+impl KsmDirOps {
+    pub fn new_inode(parent: Weak<dyn Inode>) -> Arc<dyn Inode> {
+        ProcDirBuilder::new(Self).parent(parent).build().unwrap()
+    }
+}
+
+impl DirOps for KsmDirOps {
+    fn lookup_child(&self, this_ptr: Weak<dyn Inode>, name: &str) -> Result<Arc<dyn Inode>> {
+        let Some(file) = KsmFile::from_name(name) else {
+            return_errno!(Errno::ENOENT);
+        };
+        Ok(KsmFileOps::new_inode(file, this_ptr))
+    }
+
+    fn populate_children(&self, this_ptr: Weak<dyn Inode>) {
+        let this = {
+            let this = this_ptr.upgrade().unwrap();
+            this.downcast_ref::<ProcDir<KsmDirOps>>().unwrap().this()
+        };
+        let mut cached_children = this.cached_children().write();
+        for file in KsmFile::ALL {
+            cached_children.put_entry_if_not_found(file.name(), || {
+                KsmFileOps::new_inode(file, this_ptr.clone())
+            });
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+enum KsmFile {
+    Run,
+    PagesToScan,
+    SleepMillisecs,
+    FullScans,
+    PagesShared,
+    PagesSharing,
+}
+
+impl KsmFile {
+    const ALL: [Self; 6] = [
+        Self::Run,
+        Self::PagesToScan,
+        Self::SleepMillisecs,
+        Self::FullScans,
+        Self::PagesShared,
+        Self::PagesSharing,
+    ];
+
+    fn name(self) -> &'static str {
+        match self {
+            Self::Run => "run",
+            Self::PagesToScan => "pages_to_scan",
+            Self::SleepMillisecs => "sleep_millisecs",
+            Self::FullScans => "full_scans",
+            Self::PagesShared => "pages_shared",
+            Self::PagesSharing => "pages_sharing",
+        }
+    }
+
+    fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|file| file.name() == name)
+    }
+
+    fn is_writable(self) -> bool {
+        matches!(self, Self::Run | Self::PagesToScan | Self::SleepMillisecs)
+    }
+}
+
+/// Represents the inodes at `/proc/sys/vm/ksm/*`.
+struct KsmFileOps(KsmFile);
+
+impl KsmFileOps {
+    fn new_inode(file: KsmFile, parent: Weak<dyn Inode>) -> Arc<dyn Inode> {
+        let mode = if file.is_writable() { 0o644 } else { 0o444 };
+        ProcFileBuilder::new(Self(file))
+            .parent(parent)
+            .mode(InodeMode::from_bits_truncate(mode))
+            .build()
+            .unwrap()
+    }
+}
+
+impl FileOps for KsmFileOps {
+    fn data(&self) -> Result<Vec<u8>> {
+        let output = match self.0 {
+            KsmFile::Run => format!("{}\n", ksm::run() as u32),
+            KsmFile::PagesToScan => format!("{}\n", ksm::pages_to_scan()),
+            KsmFile::SleepMillisecs => format!("{}\n", ksm::sleep_millisecs()),
+            KsmFile::FullScans => format!("{}\n", ksm::full_scans()),
+            KsmFile::PagesShared => format!("{}\n", ksm::pages_shared()),
+            KsmFile::PagesSharing => format!("{}\n", ksm::pages_sharing()),
+        };
+        Ok(output.into_bytes())
+    }
+
+    fn write_data(&self, data: &[u8]) -> Result<()> {
+        if !self.0.is_writable() {
+            return_errno_with_message!(Errno::EPERM, "the file is read-only");
+        }
+
+        let value = core::str::from_utf8(data)
+            .ok()
+            .and_then(|data| data.trim().parse::<u64>().ok())
+            .ok_or_else(|| Error::with_message(Errno::EINVAL, "the value is invalid"))?;
+
+        match self.0 {
+            KsmFile::Run => {
+                let run = u32::try_from(value)
+                    .ok()
+                    .and_then(|value| KsmRun::try_from(value).ok())
+                    .ok_or_else(|| Error::with_message(Errno::EINVAL, "the value is invalid"))?;
+                ksm::set_run(run)?;
+            }
+            KsmFile::PagesToScan => ksm::set_pages_to_scan(value as usize),
+            KsmFile::SleepMillisecs => ksm::set_sleep_millisecs(value),
+            _ => unreachable!(),
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,55 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! The `/proc/sys/vm/wx_policy` file, which controls the write-xor-execute policy of
+//! user mappings.
+//!
+//! See [`crate::vm::wx`] for the meaning of the values.
+
+use alloc::format;
+
+use crate::{
+    fs::{
+        procfs::template::{FileOps, ProcFileBuilder},
+        utils::{Inode, InodeMode},
+    },
+    prelude::*,
+    process::{credentials::capabilities::CapSet, posix_thread::AsPosixThread},
+    vm::wx,
+};
+
+/// Represents the inode at `/proc/sys/vm/wx_policy`.
+pub struct WxPolicyFileOps;
+
+impl WxPolicyFileOps {
+    pub fn new_inode(parent: Weak<dyn Inode>) -> Arc<dyn Inode> {
+        ProcFileBuilder::new(Self)
+            .parent(parent)
+            .mode(InodeMode::from_bits_truncate(0o644))
+            .build()
+            .unwrap()
+    }
+}
+
+impl FileOps for WxPolicyFileOps {
+    fn data(&self) -> Result<Vec<u8>> {
+        let output = format!("{}\n", wx::wx_policy());
+        Ok(output.into_bytes())
+    }
+
+    fn write_data(&self, data: &[u8]) -> Result<()> {
+        let is_privileged = {
+            let current = current_thread!();
+            let credentials = current.as_posix_thread().unwrap().credentials();
+            credentials.effective_capset().contains(CapSet::SYS_ADMIN)
+        };
+        if !is_privileged {
+            return_errno_with_message!(Errno::EPERM, "changing the value requires CAP_SYS_ADMIN");
+        }
+
+        let value = core::str::from_utf8(data)
+            .ok()
+            .and_then(|data| data.trim().parse::<u8>().ok())
+            .ok_or_else(|| Error::with_message(Errno::EINVAL, "the value is invalid"))?;
+        wx::set_wx_policy(value)
+    }
+}
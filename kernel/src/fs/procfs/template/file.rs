@@ -17,15 +17,11 @@ pub struct ProcFile<F: FileOps> {
 }
 
 impl<F: FileOps> ProcFile<F> {
-    pub fn new(file: F, fs: Weak<dyn FileSystem>, is_volatile: bool) -> Arc<Self> {
+    pub fn new(file: F, fs: Weak<dyn FileSystem>, mode: InodeMode, is_volatile: bool) -> Arc<Self> {
         let common = {
             let arc_fs = fs.upgrade().unwrap();
             let procfs = arc_fs.downcast_ref::<ProcFS>().unwrap();
-            let metadata = Metadata::new_file(
-                procfs.alloc_id(),
-                InodeMode::from_bits_truncate(0o444),
-                super::BLOCK_SIZE,
-            );
+            let metadata = Metadata::new_file(procfs.alloc_id(), mode, super::BLOCK_SIZE);
             Common::new(metadata, fs, is_volatile)
         };
         Arc::new(Self {
@@ -33,6 +29,10 @@ impl<F: FileOps> ProcFile<F> {
             common,
         })
     }
+
+    pub fn inner(&self) -> &F {
+        &self.inner
+    }
 }
 
 #[inherit_methods(from = "self.common")]
@@ -55,7 +55,11 @@ impl<F: FileOps + 'static> Inode for ProcFile<F> {
     fn fs(&self) -> Arc<dyn FileSystem>;
 
     fn resize(&self, _new_size: usize) -> Result<()> {
-        Err(Error::new(Errno::EPERM))
+        // Opening the writable files with `O_TRUNC` is allowed, but it has no effects.
+        if !self.common.metadata().mode.is_owner_writable() {
+            return_errno_with_message!(Errno::EPERM, "the file is read-only");
+        }
+        Ok(())
     }
 
     fn type_(&self) -> InodeType {
@@ -63,24 +67,27 @@ impl<F: FileOps + 'static> Inode for ProcFile<F> {
     }
 
     fn read_at(&self, offset: usize, writer: &mut VmWriter) -> Result<usize> {
-        let data = self.inner.data()?;
-        let start = data.len().min(offset);
-        let end = data.len().min(offset + writer.avail());
-        let len = end - start;
-        writer.write_fallible(&mut (&data[start..end]).into())?;
-        Ok(len)
+        self.inner.read_at(offset, writer)
     }
 
     fn read_direct_at(&self, offset: usize, writer: &mut VmWriter) -> Result<usize> {
         self.read_at(offset, writer)
     }
 
-    fn write_at(&self, _offset: usize, _reader: &mut VmReader) -> Result<usize> {
-        Err(Error::new(Errno::EPERM))
+    fn write_at(&self, _offset: usize, reader: &mut VmReader) -> Result<usize> {
+        let len = reader.remain();
+        if len > PAGE_SIZE {
+            return_errno_with_message!(Errno::EINVAL, "the input is too long");
+        }
+
+        let mut data = vec![0u8; len];
+        reader.read_fallible(&mut VmWriter::from(data.as_mut_slice()))?;
+        self.inner.write_data(&data)?;
+        Ok(len)
     }
 
-    fn write_direct_at(&self, _offset: usize, _reader: &mut VmReader) -> Result<usize> {
-        Err(Error::new(Errno::EPERM))
+    fn write_direct_at(&self, offset: usize, reader: &mut VmReader) -> Result<usize> {
+        self.write_at(offset, reader)
     }
 
     fn read_link(&self) -> Result<String> {
@@ -102,4 +109,24 @@ impl<F: FileOps + 'static> Inode for ProcFile<F> {
 
 pub trait FileOps: Sync + Send {
     fn data(&self) -> Result<Vec<u8>>;
+
+    /// Reads the data at `offset` into `writer`.
+    ///
+    /// The default implementation generates all the data with [`Self::data`]. Files whose data
+    /// are too large to be generated at once (e.g., `/proc/[pid]/pagemap`) should override it.
+    fn read_at(&self, offset: usize, writer: &mut VmWriter) -> Result<usize> {
+        let data = self.data()?;
+        let start = data.len().min(offset);
+        let end = data.len().min(offset + writer.avail());
+        let len = end - start;
+        writer.write_fallible(&mut (&data[start..end]).into())?;
+        Ok(len)
+    }
+
+    /// Writes the data to the file.
+    ///
+    /// Most files are read-only, so the default implementation fails with `EPERM`.
+    fn write_data(&self, _data: &[u8]) -> Result<()> {
+        Err(Error::new(Errno::EPERM))
+    }
 }
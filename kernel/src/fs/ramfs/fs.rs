@@ -87,6 +87,10 @@ impl FileSystem for RamFS {
     fn flags(&self) -> FsFlags {
         FsFlags::DENTRY_UNEVICTABLE
     }
+
+    fn name(&self) -> &'static str {
+        "ramfs"
+    }
 }
 
 /// An inode of `RamFs`.
@@ -0,0 +1,243 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! In-kernel data transfer between files.
+//!
+//! The transfer is the common part of `sendfile`, `copy_file_range` and `splice`.
+//! The data never goes through the user space. If the source is a regular file
+//! with a page cache, the data is written to the destination directly from the
+//! pages of the page cache. Otherwise, the data is staged in a kernel buffer.
+
+use align_ext::AlignExt;
+use aster_rights::Full;
+
+use super::{
+    file_handle::FileLike,
+    inode_handle::InodeHandle,
+    pipe::{PipeReader, PipeWriter},
+    utils::{InodeType, SeekFrom, StatusFlags},
+};
+use crate::{events::IoEvents, prelude::*, vm::vmo::Vmo};
+
+/// The size of the kernel buffer used to stage the data.
+const BUFFER_SIZE: usize = PAGE_SIZE;
+
+/// An endpoint of a transfer.
+pub struct SpliceEnd<'a> {
+    file: &'a Arc<dyn FileLike>,
+    /// The explicit offset to read from or write to.
+    ///
+    /// If the offset is `None`, the file offset will be used and updated.
+    /// Otherwise, the file offset is left untouched.
+    offset: Option<&'a mut usize>,
+}
+
+impl<'a> SpliceEnd<'a> {
+    /// Creates an endpoint of a transfer.
+    pub fn new(file: &'a Arc<dyn FileLike>, offset: Option<&'a mut usize>) -> Self {
+        Self { file, offset }
+    }
+
+    fn read(&mut self, writer: &mut VmWriter, is_nonblocking: bool) -> Result<usize> {
+        if let Some(offset) = self.offset.as_mut() {
+            let len = self.file.read_at(**offset, writer)?;
+            **offset += len;
+            return Ok(len);
+        }
+
+        if is_nonblocking && let Some(pipe_reader) = self.file.downcast_ref::<PipeReader>() {
+            return pipe_reader.try_read(writer);
+        }
+        self.file.read(writer)
+    }
+
+    fn write(&mut self, reader: &mut VmReader, is_nonblocking: bool) -> Result<usize> {
+        if let Some(offset) = self.offset.as_mut() {
+            let len = self.file.write_at(**offset, reader)?;
+            **offset += len;
+            return Ok(len);
+        }
+
+        if is_nonblocking && let Some(pipe_writer) = self.file.downcast_ref::<PipeWriter>() {
+            return pipe_writer.try_write(reader);
+        }
+        self.file.write(reader)
+    }
+
+    /// Returns `len` bytes that have been read to the source, so that they will be read again.
+    ///
+    /// This fails if the source is not seekable (e.g., a pipe or a socket).
+    fn unread(&mut self, len: usize) -> Result<()> {
+        if let Some(offset) = self.offset.as_mut() {
+            **offset -= len;
+            return Ok(());
+        }
+
+        self.file.seek(SeekFrom::Current(-(len as isize)))?;
+        Ok(())
+    }
+
+    /// Returns whether the data can be read from the file without blocking.
+    fn is_readable(&self) -> bool {
+        self.file.poll(IoEvents::IN, None).contains(IoEvents::IN)
+    }
+
+    /// Returns the page cache of the file if the data can be read from it directly.
+    fn page_cache(&self) -> Option<(Vmo<Full>, usize)> {
+        let inode_handle = self.file.downcast_ref::<InodeHandle>()?;
+        if inode_handle.status_flags().contains(StatusFlags::O_DIRECT) {
+            return None;
+        }
+
+        let inode = inode_handle.dentry().inode();
+        if inode.type_() != InodeType::File {
+            return None;
+        }
+        let page_cache = inode.page_cache()?;
+        Some((page_cache, inode.size()))
+    }
+}
+
+/// Transfers at most `count` bytes from `src` to `dst`.
+///
+/// The transfer stops at the end of `src`, or once `dst` accepts fewer bytes than
+/// requested. If `is_nonblocking` is true, the operations on pipes do not block.
+/// Otherwise, only the first read from `src` may block. Once some bytes have been
+/// transferred, the transfer also stops if `src` (e.g., a pipe or a socket) has no
+/// more data for now.
+///
+/// Errors are reported only if no bytes have been transferred,
+/// otherwise the number of transferred bytes is returned.
+pub fn splice(
+    mut src: SpliceEnd,
+    mut dst: SpliceEnd,
+    count: usize,
+    is_nonblocking: bool,
+) -> Result<usize> {
+    if !src.file.access_mode().is_readable() {
+        return_errno_with_message!(Errno::EBADF, "the source file is not readable");
+    }
+    if !dst.file.access_mode().is_writable() {
+        return_errno_with_message!(Errno::EBADF, "the destination file is not writable");
+    }
+
+    let page_cache = src.page_cache();
+    let mut buffer = None;
+    let mut total_len = 0;
+
+    while total_len < count {
+        let max_len = count - total_len;
+        let res = if let Some((page_cache, file_size)) = page_cache.as_ref() {
+            splice_from_page_cache(
+                &mut src,
+                page_cache,
+                *file_size,
+                &mut dst,
+                max_len,
+                is_nonblocking,
+            )
+        } else {
+            if total_len > 0 && !src.is_readable() {
+                break;
+            }
+            let buffer = buffer.get_or_insert_with(|| vec![0u8; BUFFER_SIZE].into_boxed_slice());
+            splice_via_buffer(&mut src, buffer, &mut dst, max_len, is_nonblocking)
+        };
+
+        match res {
+            Ok((read_len, written_len)) => {
+                total_len += written_len;
+                if read_len == 0 || written_len < read_len {
+                    break;
+                }
+                // A short read means that `src` has no more data for now.
+                if page_cache.is_none() && read_len < BUFFER_SIZE.min(max_len) {
+                    break;
+                }
+            }
+            Err(err) if total_len > 0 => {
+                debug!("the transfer stops due to {:?}", err);
+                break;
+            }
+            Err(err) => return Err(err),
+        }
+    }
+
+    Ok(total_len)
+}
+
+/// Transfers data within one page from the page cache of `src` to `dst`.
+///
+/// Returns the number of bytes read and the number of bytes written.
+fn splice_from_page_cache(
+    src: &mut SpliceEnd,
+    page_cache: &Vmo<Full>,
+    file_size: usize,
+    dst: &mut SpliceEnd,
+    max_len: usize,
+    is_nonblocking: bool,
+) -> Result<(usize, usize)> {
+    let pos = match src.offset.as_ref() {
+        Some(offset) => **offset,
+        None => src.file.seek(SeekFrom::Current(0))?,
+    };
+    if pos >= file_size {
+        return Ok((0, 0));
+    }
+
+    let page_offset = pos % PAGE_SIZE;
+    let read_len = max_len.min(file_size - pos).min(PAGE_SIZE - page_offset);
+    let page = page_cache.commit_page(pos.align_down(PAGE_SIZE))?;
+    let mut reader = page
+        .reader()
+        .skip(page_offset)
+        .limit(read_len)
+        .to_fallible();
+
+    let written_len = dst.write(&mut reader, is_nonblocking)?;
+
+    match src.offset.as_mut() {
+        Some(offset) => **offset += written_len,
+        None => {
+            src.file.seek(SeekFrom::Start(pos + written_len))?;
+        }
+    }
+    Ok((read_len, written_len))
+}
+
+/// Transfers data from `src` to `dst` through the kernel `buffer`.
+///
+/// Returns the number of bytes read and the number of bytes written. If `dst` fails,
+/// the bytes that are not written are returned to `src`, and the error is reported
+/// unless some bytes have been written. If the bytes cannot be returned (e.g., `src`
+/// is a pipe), they are lost and the error is always reported.
+fn splice_via_buffer(
+    src: &mut SpliceEnd,
+    buffer: &mut [u8],
+    dst: &mut SpliceEnd,
+    max_len: usize,
+    is_nonblocking: bool,
+) -> Result<(usize, usize)> {
+    let buf_len = buffer.len().min(max_len);
+    let read_len = {
+        let mut writer = VmWriter::from(&mut buffer[..buf_len]).to_fallible();
+        src.read(&mut writer, is_nonblocking)?
+    };
+
+    // The data has been consumed from the source, so try to write all of it.
+    let mut written_len = 0;
+    while written_len < read_len {
+        let mut reader = VmReader::from(&buffer[written_len..read_len]).to_fallible();
+        match dst.write(&mut reader, is_nonblocking) {
+            Ok(0) => break,
+            Ok(len) => written_len += len,
+            Err(err) => {
+                if src.unread(read_len - written_len).is_err() || written_len == 0 {
+                    return Err(err);
+                }
+                return Ok((written_len, written_len));
+            }
+        }
+    }
+
+    Ok((read_len, written_len))
+}
@@ -35,6 +35,11 @@ const PIPE_BUF: usize = 4096;
 #[cfg(ktest)]
 const PIPE_BUF: usize = 2;
 
+/// The auxiliary data attached to the data in a channel.
+///
+/// For example, UNIX domain sockets attach the files passed via `SCM_RIGHTS` to the data.
+pub type AuxData = Box<dyn Any + Send + Sync>;
+
 impl<T> Channel<T> {
     /// Creates a new channel with the given capacity.
     ///
@@ -95,6 +100,10 @@ macro_rules! impl_common_methods_for_channel {
             self.0.common.is_shutdown()
         }
 
+        pub fn capacity(&self) -> usize {
+            self.0.common.capacity()
+        }
+
         pub fn poll(&self, mask: IoEvents, poller: Option<&mut PollHandle>) -> IoEvents {
             self.this_end()
                 .pollee
@@ -135,6 +144,21 @@ impl Producer<u8> {
     /// - Returns `Err(EPIPE)` if the channel is shut down.
     /// - Returns `Err(EAGAIN)` if the channel is full.
     pub fn try_write(&self, reader: &mut dyn MultiRead) -> Result<usize> {
+        self.try_write_with_aux(reader, &mut None)
+    }
+
+    /// Tries to write `buf` to the channel with the auxiliary data attached to it.
+    ///
+    /// The auxiliary data is taken out of `aux` only if some bytes are written. A subsequent
+    /// [`Consumer::try_read_with_aux`] will never read the data written before or after this
+    /// write together with the auxiliary data.
+    ///
+    /// See [`Self::try_write`] for the return values.
+    pub fn try_write_with_aux(
+        &self,
+        reader: &mut dyn MultiRead,
+        aux: &mut Option<AuxData>,
+    ) -> Result<usize> {
         if reader.is_empty() {
             // Even after shutdown, writing an empty buffer is still fine.
             return Ok(0);
@@ -144,7 +168,28 @@ impl Producer<u8> {
             return_errno_with_message!(Errno::EPIPE, "the channel is shut down");
         }
 
-        let written_len = self.0.write(reader)?;
+        let written_len = {
+            let mut records = self.0.common.records.lock();
+            let pending_len = self.this_end().rb().len();
+            let written_len = self.0.write(reader)?;
+            if written_len > 0 && aux.is_some() {
+                let record = Record::new(written_len, false, aux.take());
+                push_record(&mut records, pending_len, record);
+            } else if written_len > 0 && !records.is_empty() {
+                // The stream data must be recorded if there are pending packets. Otherwise, the
+                // stream data before the packets will be mistaken for part of the packets.
+                //
+                // The stream data is not merged into a record with auxiliary data, so that the
+                // data after the auxiliary data will not be read together with it.
+                match records.back_mut() {
+                    Some(record) if !record.is_packet && record.aux.is_none() => {
+                        record.len += written_len
+                    }
+                    _ => records.push_back(Record::new(written_len, false, None)),
+                }
+            }
+            written_len
+        };
         self.peer_end().pollee.notify(IoEvents::IN);
 
         if written_len > 0 {
@@ -153,6 +198,86 @@ impl Producer<u8> {
             return_errno_with_message!(Errno::EAGAIN, "the channel is full");
         }
     }
+
+    /// Tries to write a packet to the channel.
+    ///
+    /// At most [`PIPE_BUF`] bytes are taken from `reader` to form the packet, which is
+    /// written atomically. The packet boundary is preserved, so that a subsequent
+    /// [`Consumer::try_read`] will read the packet as a whole.
+    ///
+    /// - Returns `Ok(_)` with the length of the packet if successful.
+    /// - Returns `Err(EPIPE)` if the channel is shut down.
+    /// - Returns `Err(EAGAIN)` if the channel does not have enough space for the packet.
+    pub fn try_write_packet(&self, reader: &mut dyn MultiRead) -> Result<usize> {
+        let packet_len = reader.sum_lens().min(PIPE_BUF);
+        self.write_packet(reader, packet_len, &mut None)
+    }
+
+    /// Tries to write a message to the channel with the auxiliary data attached to it.
+    ///
+    /// The message consists of all the bytes in `reader` and is written atomically as a packet.
+    /// The auxiliary data is taken out of `aux` only if the message is written.
+    ///
+    /// - Returns `Ok(_)` with the length of the message if successful.
+    /// - Returns `Err(EMSGSIZE)` if the message is larger than the capacity of the channel.
+    /// - Returns `Err(EPIPE)` if the channel is shut down.
+    /// - Returns `Err(EAGAIN)` if the channel does not have enough space for the message.
+    pub fn try_write_message(
+        &self,
+        reader: &mut dyn MultiRead,
+        aux: &mut Option<AuxData>,
+    ) -> Result<usize> {
+        let message_len = reader.sum_lens();
+        if message_len > self.capacity() {
+            return_errno_with_message!(Errno::EMSGSIZE, "the message is too long");
+        }
+
+        self.write_packet(reader, message_len, aux)
+    }
+
+    fn write_packet(
+        &self,
+        reader: &mut dyn MultiRead,
+        packet_len: usize,
+        aux: &mut Option<AuxData>,
+    ) -> Result<usize> {
+        if packet_len == 0 {
+            return Ok(0);
+        }
+
+        if self.is_shutdown() {
+            return_errno_with_message!(Errno::EPIPE, "the channel is shut down");
+        }
+
+        {
+            let mut records = self.0.common.records.lock();
+            let mut rb = self.this_end().rb();
+            if rb.free_len() < packet_len {
+                return_errno_with_message!(Errno::EAGAIN, "the channel is full");
+            }
+
+            let mut packet = vec![0u8; packet_len];
+            reader.read(&mut VmWriter::from(packet.as_mut_slice()))?;
+            let pending_len = rb.len();
+            rb.write_fallible(&mut VmReader::from(packet.as_slice()).to_fallible())?;
+            let record = Record::new(packet_len, true, aux.take());
+            push_record(&mut records, pending_len, record);
+        }
+        self.peer_end().pollee.notify(IoEvents::IN);
+
+        Ok(packet_len)
+    }
+
+    /// Sets the capacity of the channel.
+    ///
+    /// The data in the channel is preserved. The new capacity must be a power of two.
+    ///
+    /// - Returns `Err(EBUSY)` if the data in the channel cannot fit into the new capacity.
+    pub fn set_capacity(&self, new_capacity: usize) -> Result<()> {
+        self.0.common.set_capacity(new_capacity)?;
+        self.this_end().pollee.notify(IoEvents::OUT);
+        Ok(())
+    }
 }
 
 impl<T: Pod> Producer<T> {
@@ -216,25 +341,85 @@ impl Consumer<u8> {
     /// - Returns `Ok(0)` if the channel is shut down and there is no data left.
     /// - Returns `Err(EAGAIN)` if the channel is empty.
     pub fn try_read(&self, writer: &mut dyn MultiWrite) -> Result<usize> {
+        self.try_read_with_aux(writer).map(|(read_len, _)| read_len)
+    }
+
+    /// Tries to read `buf` from the channel with the auxiliary data attached to it.
+    ///
+    /// The auxiliary data is returned along with the first read of the data that it is attached
+    /// to. A read never crosses the boundaries of the data that auxiliary data is attached to.
+    ///
+    /// See [`Self::try_read`] for the return values.
+    pub fn try_read_with_aux(
+        &self,
+        writer: &mut dyn MultiWrite,
+    ) -> Result<(usize, Option<AuxData>)> {
         if writer.is_empty() {
-            return Ok(0);
+            return Ok((0, None));
         }
 
         // This must be recorded before the actual operation to avoid race conditions.
         let is_shutdown = self.is_shutdown();
 
-        let read_len = self.0.read(writer)?;
+        let (read_len, aux) = {
+            let mut records = self.0.common.records.lock();
+            if let Some(record) = records.front_mut() {
+                let res = self.read_record(record, writer);
+                if record.len == 0 {
+                    records.pop_front();
+                }
+                res?
+            } else {
+                (self.0.read(writer)?, None)
+            }
+        };
         self.peer_end().pollee.notify(IoEvents::OUT);
         self.this_end().pollee.invalidate();
 
         if read_len > 0 {
-            Ok(read_len)
+            Ok((read_len, aux))
         } else if is_shutdown {
-            Ok(0)
+            Ok((0, None))
         } else {
             return_errno_with_message!(Errno::EAGAIN, "the channel is empty");
         }
     }
+
+    /// Reads the data of the first record.
+    ///
+    /// A packet is always consumed as a whole. If `writer` cannot hold the entire
+    /// packet, the remaining bytes of the packet are discarded.
+    fn read_record(
+        &self,
+        record: &mut Record,
+        writer: &mut dyn MultiWrite,
+    ) -> Result<(usize, Option<AuxData>)> {
+        let len = if record.is_packet {
+            record.len
+        } else {
+            record.len.min(writer.sum_lens())
+        };
+
+        let mut buf = vec![0u8; len];
+        let read_len = self
+            .0
+            .read(&mut VmWriter::from(buf.as_mut_slice()).to_fallible())?;
+        debug_assert_eq!(read_len, len);
+        record.len -= read_len;
+        let aux = record.aux.take();
+
+        let written_len = writer.write(&mut VmReader::from(&buf[..read_len]))?;
+        Ok((written_len, aux))
+    }
+
+    /// Sets the capacity of the channel.
+    ///
+    /// See [`Producer::set_capacity`] for more details.
+    pub fn set_capacity(&self, new_capacity: usize) -> Result<()> {
+        self.0.common.set_capacity(new_capacity)?;
+        self.peer_end().pollee.notify(IoEvents::OUT);
+        Ok(())
+    }
 }
 
 impl<T: Pod> Consumer<T> {
@@ -320,9 +505,43 @@ impl<T: Pod, R: TRights> Fifo<T, R> {
 struct Common<T> {
     producer: FifoInner<RbProducer<T>>,
     consumer: FifoInner<RbConsumer<T>>,
+    /// The boundaries of the data in the channel.
+    ///
+    /// The records are only maintained when there are packets in the channel.
+    /// If the queue is empty, all the data in the channel is stream data.
+    records: Mutex<VecDeque<Record>>,
     is_shutdown: AtomicBool,
 }
 
+/// A contiguous part of the data in the channel.
+struct Record {
+    len: usize,
+    is_packet: bool,
+    /// The auxiliary data attached to the data, which has not been read yet.
+    aux: Option<AuxData>,
+}
+
+impl Record {
+    fn new(len: usize, is_packet: bool, aux: Option<AuxData>) -> Self {
+        Self {
+            len,
+            is_packet,
+            aux,
+        }
+    }
+}
+
+/// Pushes a new record for the data that has just been written.
+///
+/// `pending_len` is the length of the data in the channel before the write. If the records are
+/// not maintained yet, the pending data is all stream data, so it is recorded first.
+fn push_record(records: &mut VecDeque<Record>, pending_len: usize, record: Record) {
+    if records.is_empty() && pending_len > 0 {
+        records.push_back(Record::new(pending_len, false, None));
+    }
+    records.push_back(record);
+}
+
 impl<T> Common<T> {
     fn new(
         capacity: usize,
@@ -349,6 +568,7 @@ impl<T> Common<T> {
         Self {
             producer,
             consumer,
+            records: Mutex::new(VecDeque::new()),
             is_shutdown: AtomicBool::new(false),
         }
     }
@@ -375,6 +595,33 @@ impl<T> Common<T> {
     }
 }
 
+impl Common<u8> {
+    fn set_capacity(&self, new_capacity: usize) -> Result<()> {
+        if !new_capacity.is_power_of_two() {
+            return_errno_with_message!(Errno::EINVAL, "the capacity is not a power of two");
+        }
+
+        // Lock order: records -> producer -> consumer.
+        let _records = self.records.lock();
+        let mut producer = self.producer.rb();
+        let mut consumer = self.consumer.rb();
+
+        let len = consumer.len();
+        if len > new_capacity {
+            return_errno_with_message!(Errno::EBUSY, "the data exceeds the new capacity");
+        }
+
+        let (mut new_producer, new_consumer) = RingBuffer::<u8>::new(new_capacity).split();
+        let mut buf = vec![0u8; len];
+        consumer.read_fallible(&mut VmWriter::from(buf.as_mut_slice()).to_fallible())?;
+        new_producer.write_fallible(&mut VmReader::from(buf.as_slice()).to_fallible())?;
+
+        *producer = new_producer;
+        *consumer = new_consumer;
+        Ok(())
+    }
+}
+
 struct FifoInner<T> {
     rb: Mutex<T>,
     pollee: Pollee,
@@ -424,4 +671,92 @@ mod test {
             .unwrap();
         assert_eq!(data, expected_data);
     }
+
+    #[ktest]
+    fn test_channel_packets() {
+        let channel = Channel::with_capacity(16);
+        let (producer, consumer) = channel.split();
+
+        let write_packet = |data: &[u8]| {
+            producer
+                .try_write_packet(&mut VmReader::from(data).to_fallible())
+                .unwrap()
+        };
+        let read = |buf: &mut [u8]| {
+            consumer
+                .try_read(&mut VmWriter::from(buf).to_fallible())
+                .unwrap()
+        };
+
+        // A packet contains at most `PIPE_BUF` bytes.
+        assert_eq!(write_packet(&[1, 2, 3]), PIPE_BUF);
+        assert_eq!(write_packet(&[4]), 1);
+        producer
+            .try_write(&mut VmReader::from([5u8, 6].as_slice()).to_fallible())
+            .unwrap();
+
+        // The packet boundaries are preserved, and the excess bytes are discarded.
+        let mut buf = [0u8; 4];
+        assert_eq!(read(&mut buf[..1]), 1);
+        assert_eq!(buf[0], 1);
+        assert_eq!(read(&mut buf), 1);
+        assert_eq!(buf[0], 4);
+        assert_eq!(read(&mut buf), 2);
+        assert_eq!(buf[..2], [5, 6]);
+    }
+
+    #[ktest]
+    fn test_channel_aux() {
+        let channel = Channel::with_capacity(16);
+        let (producer, consumer) = channel.split();
+
+        let write = |data: &[u8], aux: Option<u32>| {
+            let mut aux = aux.map(|aux| Box::new(aux) as AuxData);
+            producer
+                .try_write_with_aux(&mut VmReader::from(data).to_fallible(), &mut aux)
+                .unwrap();
+            assert!(aux.is_none());
+        };
+        let read = |buf: &mut [u8]| {
+            let (read_len, aux) = consumer
+                .try_read_with_aux(&mut VmWriter::from(buf).to_fallible())
+                .unwrap();
+            (read_len, aux.map(|aux| *aux.downcast::<u32>().unwrap()))
+        };
+
+        write(&[1, 2], None);
+        write(&[3, 4], Some(42));
+        write(&[5], None);
+
+        // The data before or after the auxiliary data is not read together with it.
+        let mut buf = [0u8; 8];
+        assert_eq!(read(&mut buf), (2, None));
+        assert_eq!(buf[..2], [1, 2]);
+        assert_eq!(read(&mut buf[..1]), (1, Some(42)));
+        assert_eq!(buf[0], 3);
+        assert_eq!(read(&mut buf), (1, None));
+        assert_eq!(buf[0], 4);
+        assert_eq!(read(&mut buf), (1, None));
+        assert_eq!(buf[0], 5);
+    }
+
+    #[ktest]
+    fn test_channel_set_capacity() {
+        let channel = Channel::with_capacity(4);
+        let (producer, consumer) = channel.split();
+
+        producer
+            .try_write(&mut VmReader::from([1u8, 2, 3].as_slice()).to_fallible())
+            .unwrap();
+        assert_eq!(producer.set_capacity(2).unwrap_err().error(), Errno::EBUSY);
+
+        producer.set_capacity(8).unwrap();
+        assert_eq!(consumer.capacity(), 8);
+
+        let mut buf = [0u8; 8];
+        let read_len = consumer
+            .try_read(&mut VmWriter::from(buf.as_mut_slice()).to_fallible())
+            .unwrap();
+        assert_eq!(buf[..read_len], [1, 2, 3]);
+    }
 }
@@ -51,6 +51,9 @@ pub trait FileSystem: Any + Sync + Send {
     fn sb(&self) -> SuperBlock;
 
     fn flags(&self) -> FsFlags;
+
+    /// Returns the name of the file system type, e.g., "ext2".
+    fn name(&self) -> &'static str;
 }
 
 impl dyn FileSystem {
@@ -62,6 +65,7 @@ impl dyn FileSystem {
 impl Debug for dyn FileSystem {
     fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
         f.debug_struct("FileSystem")
+            .field("name", &self.name())
             .field("super_block", &self.sb())
             .field("flags", &self.flags())
             .finish()
@@ -4,6 +4,7 @@
 
 use core::{any::TypeId, time::Duration};
 
+use align_ext::AlignExt;
 use aster_rights::Full;
 use core2::io::{Error as IoError, ErrorKind as IoErrorKind, Result as IoResult, Write};
 use ostd::task::Task;
@@ -13,7 +14,10 @@ use crate::{
     events::IoEvents,
     fs::device::{Device, DeviceType},
     prelude::*,
-    process::{posix_thread::AsPosixThread, signal::PollHandle, Gid, Uid},
+    process::{
+        credentials::capabilities::CapSet, posix_thread::AsPosixThread, signal::PollHandle, Gid,
+        Uid,
+    },
     time::clocks::RealTimeCoarseClock,
     vm::vmo::Vmo,
 };
@@ -386,16 +390,22 @@ pub trait Inode: Any + Sync + Send {
         Err(Error::new(Errno::EISDIR))
     }
 
+    /// Reads data bypassing the page cache.
+    ///
+    /// File systems that cannot perform direct I/O fall back to buffered I/O.
     fn read_direct_at(&self, offset: usize, writer: &mut VmWriter) -> Result<usize> {
-        Err(Error::new(Errno::EISDIR))
+        self.read_at(offset, writer)
     }
 
     fn write_at(&self, offset: usize, reader: &mut VmReader) -> Result<usize> {
         Err(Error::new(Errno::EISDIR))
     }
 
+    /// Writes data bypassing the page cache.
+    ///
+    /// File systems that cannot perform direct I/O fall back to buffered I/O.
     fn write_direct_at(&self, offset: usize, reader: &mut VmReader) -> Result<usize> {
-        Err(Error::new(Errno::EISDIR))
+        self.write_at(offset, reader)
     }
 
     fn create(&self, name: &str, type_: InodeType, mode: InodeMode) -> Result<Arc<dyn Inode>> {
@@ -410,6 +420,14 @@ pub trait Inode: Any + Sync + Send {
         None
     }
 
+    /// Prepares the inode to be opened, which fails if the inode cannot be opened.
+    ///
+    /// For example, an encrypted file cannot be opened without its key. This method is not
+    /// called for the files opened with `O_PATH`.
+    fn prepare_open(&self) -> Result<()> {
+        Ok(())
+    }
+
     fn readdir_at(&self, offset: usize, visitor: &mut dyn DirentVisitor) -> Result<usize> {
         Err(Error::new(Errno::ENOTDIR))
     }
@@ -515,6 +533,25 @@ pub trait Inode: Any + Sync + Send {
         let metadata = self.metadata();
         let mode = metadata.mode;
 
+        // Like Linux, `CAP_DAC_OVERRIDE` bypasses all the checks, except that a file can be
+        // executed only if it is executable by someone. `CAP_DAC_READ_SEARCH` bypasses the read
+        // checks and the search checks of directories.
+        let capset = creds.effective_capset();
+        let is_dir = metadata.type_.is_directory();
+        let is_executable_by_anyone = is_dir
+            || mode.is_owner_executable()
+            || mode.is_group_executable()
+            || mode.is_other_executable();
+        if capset.contains(CapSet::DAC_OVERRIDE) && (!perm.may_exec() || is_executable_by_anyone) {
+            return Ok(());
+        }
+        if capset.contains(CapSet::DAC_READ_SEARCH)
+            && !perm.may_write()
+            && (!perm.may_exec() || is_dir)
+        {
+            return Ok(());
+        }
+
         if metadata.uid == creds.fsuid() {
             if (perm.may_read() && !mode.is_owner_readable())
                 || (perm.may_write() && !mode.is_owner_writable())
@@ -564,13 +601,19 @@ impl dyn Inode {
             return_errno!(Errno::EISDIR);
         }
 
+        // Direct I/O requires the length of the buffer to be aligned, so the
+        // buffer is extended to cover the whole last page of the file.
         let file_size = self.size();
-        if buf.len() < file_size {
-            buf.resize(file_size, 0);
+        let buf_len = file_size.align_up(PAGE_SIZE);
+        let orig_len = buf.len();
+        if orig_len < buf_len {
+            buf.resize(buf_len, 0);
         }
 
-        let mut writer = VmWriter::from(&mut buf[..file_size]).to_fallible();
-        self.read_direct_at(0, &mut writer)
+        let mut writer = VmWriter::from(&mut buf[..buf_len]).to_fallible();
+        let read_len = self.read_direct_at(0, &mut writer)?;
+        buf.truncate(orig_len.max(file_size));
+        Ok(read_len)
     }
 
     pub fn writer(&self, from_offset: usize) -> InodeWriter {
@@ -41,4 +41,50 @@ pub enum IoctlCmd {
     TIOCGPTPEER = 0x40045441,
     /// Get tdx report using TDCALL
     TDXGETREPORT = 0xc4405401,
+    /// Append a rule to the packet filter
+    NFADDRULE = 0x40384e00,
+    /// Remove all the rules of a hook from the packet filter
+    NFFLUSH = 0x4e01,
+    /// Attach a TUN/TAP file to a new iface
+    TUNSETIFF = 0x400454ca,
+    /// Get the iface that a TUN/TAP file is attached to
+    TUNGETIFF = 0x800454d2,
+    /// Negotiate the API of a userfaultfd
+    UFFDIO_API = 0xc018aa3f,
+    /// Register a range to a userfaultfd
+    UFFDIO_REGISTER = 0xc020aa00,
+    /// Unregister a range from a userfaultfd
+    UFFDIO_UNREGISTER = 0x8010aa01,
+    /// Wake up the threads waiting for the page faults in a range
+    UFFDIO_WAKE = 0x8010aa02,
+    /// Copy pages into a range registered to a userfaultfd
+    UFFDIO_COPY = 0xc028aa03,
+    /// Map zero pages into a range registered to a userfaultfd
+    UFFDIO_ZEROPAGE = 0xc020aa04,
+    /// Write-protect a range registered to a userfaultfd, or remove the protection
+    UFFDIO_WRITEPROTECT = 0xc018aa06,
+    /// Get the capabilities of a PTP clock
+    PTP_CLOCK_GETCAPS = 0x80503d01,
+    PTP_CLOCK_GETCAPS2 = 0x80503d0a,
+    /// Measure the offset between a PTP clock and the system clock
+    PTP_SYS_OFFSET = 0x43403d05,
+    PTP_SYS_OFFSET2 = 0x43403d0e,
+    /// Measure the offset between a PTP clock and the system clocks with cross timestamps
+    PTP_SYS_OFFSET_PRECISE = 0xc0403d08,
+    PTP_SYS_OFFSET_PRECISE2 = 0xc0403d11,
+    /// Get the entropy count of the input pool of the random number generator
+    RNDGETENTCNT = 0x80045200,
+    /// Credit the input pool of the random number generator with entropy
+    RNDADDTOENTCNT = 0x40045201,
+    /// Mix bytes into the input pool of the random number generator and credit entropy
+    RNDADDENTROPY = 0x40085203,
+    /// Clear the entropy count of the input pool, which is a no-op like Linux
+    RNDZAPENTCNT = 0x5204,
+    RNDCLEARPOOL = 0x5206,
+    /// Reseed the CRNG of the random number generator
+    RNDRESEEDCRNG = 0x5207,
+    /// Set the encryption policy of an empty directory
+    FS_IOC_SET_ENCRYPTION_POLICY = 0x800c6613,
+    /// Get the encryption policy of an encrypted file or directory
+    FS_IOC_GET_ENCRYPTION_POLICY = 0x400c6615,
 }
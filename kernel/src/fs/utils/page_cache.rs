@@ -15,6 +15,7 @@ use lru::LruCache;
 use ostd::{
     impl_untyped_frame_meta_for,
     mm::{Frame, FrameAllocOptions, UFrame, UntypedMem, VmIo},
+    sync::{PiMutex, PiMutexGuard},
 };
 
 use crate::{
@@ -249,7 +250,7 @@ impl ReadaheadState {
     /// Waits for the previous readahead.
     pub fn wait_for_prev_readahead(
         &mut self,
-        pages: &mut MutexGuard<LruCache<usize, CachePage>>,
+        pages: &mut PiMutexGuard<LruCache<usize, CachePage>>,
     ) -> Result<()> {
         if matches!(self.waiter.wait(), Some(BioStatus::Complete)) {
             let Some(window) = &self.ra_window else {
@@ -304,7 +305,7 @@ impl ReadaheadState {
     /// Sends the relevant read request and sets the relevant page in the page cache to `Uninit`.
     pub fn conduct_readahead(
         &mut self,
-        pages: &mut MutexGuard<LruCache<usize, CachePage>>,
+        pages: &mut PiMutexGuard<LruCache<usize, CachePage>>,
         backend: Arc<dyn PageCacheBackend>,
     ) -> Result<()> {
         let Some(window) = &self.ra_window else {
@@ -331,7 +332,9 @@ impl ReadaheadState {
 }
 
 struct PageCacheManager {
-    pages: Mutex<LruCache<usize, CachePage>>,
+    pages: PiMutex<LruCache<usize, CachePage>>,
+    /// The indices of the pages that are accessed directly from the backend.
+    direct_pages: Mutex<BTreeSet<usize>>,
     backend: Weak<dyn PageCacheBackend>,
     ra_state: Mutex<ReadaheadState>,
 }
@@ -339,7 +342,8 @@ struct PageCacheManager {
 impl PageCacheManager {
     pub fn new(backend: Weak<dyn PageCacheBackend>) -> Self {
         Self {
-            pages: Mutex::new(LruCache::unbounded()),
+            pages: PiMutex::new(LruCache::unbounded()),
+            direct_pages: Mutex::new(BTreeSet::new()),
             backend,
             ra_state: Mutex::new(ReadaheadState::new()),
         }
@@ -353,8 +357,10 @@ impl PageCacheManager {
     pub fn discard_range(&self, range: Range<usize>) {
         let page_idx_range = get_page_idx_range(&range);
         let mut pages = self.pages.lock();
+        let mut direct_pages = self.direct_pages.lock();
         for idx in page_idx_range {
             pages.pop(&idx);
+            direct_pages.remove(&idx);
         }
     }
 
@@ -385,9 +391,40 @@ impl PageCacheManager {
         {
             page.store_state(PageState::UpToDate);
         }
+        drop(pages);
+
+        // The writes to the directly accessed pages only need to be persisted.
+        let has_direct_pages = self
+            .direct_pages
+            .lock()
+            .range(page_idx_range)
+            .next()
+            .is_some();
+        if has_direct_pages {
+            backend.flush_direct()?;
+        }
         Ok(())
     }
 
+    /// Gets the page at `idx` from the backend directly, bypassing the page cache.
+    ///
+    /// Returns `None` if the backend does not support direct access for the page.
+    fn direct_access(&self, idx: usize) -> Result<Option<UFrame>> {
+        let backend = self.backend();
+        let Some(frame) = backend.direct_access(idx) else {
+            return Ok(None);
+        };
+
+        // A stale copy of the page may be brought into the page cache by readahead.
+        if let Some(page) = self.pages.lock().pop(&idx) {
+            if page.load_state() == PageState::Dirty {
+                backend.write_page(idx, &page)?;
+            }
+        }
+        self.direct_pages.lock().insert(idx);
+        Ok(Some(frame))
+    }
+
     fn ondemand_readahead(&self, idx: usize) -> Result<UFrame> {
         let mut pages = self.pages.lock();
         let mut ra_state = self.ra_state.lock();
@@ -446,10 +483,17 @@ impl Debug for PageCacheManager {
 
 impl Pager for PageCacheManager {
     fn commit_page(&self, idx: usize) -> Result<UFrame> {
+        if let Some(frame) = self.direct_access(idx)? {
+            return Ok(frame);
+        }
         self.ondemand_readahead(idx)
     }
 
     fn update_page(&self, idx: usize) -> Result<()> {
+        if self.direct_pages.lock().contains(&idx) {
+            return Ok(());
+        }
+
         let mut pages = self.pages.lock();
         if let Some(page) = pages.get_mut(&idx) {
             page.store_state(PageState::Dirty);
@@ -461,6 +505,10 @@ impl Pager for PageCacheManager {
     }
 
     fn decommit_page(&self, idx: usize) -> Result<()> {
+        if self.direct_pages.lock().remove(&idx) {
+            return Ok(());
+        }
+
         let page_result = self.pages.lock().pop(&idx);
         if let Some(page) = page_result {
             if let PageState::Dirty = page.load_state() {
@@ -477,6 +525,10 @@ impl Pager for PageCacheManager {
     }
 
     fn commit_overwrite(&self, idx: usize) -> Result<UFrame> {
+        if let Some(frame) = self.direct_access(idx)? {
+            return Ok(frame);
+        }
+
         if let Some(page) = self.pages.lock().get(&idx) {
             return Ok(page.clone().into());
         }
@@ -484,6 +536,10 @@ impl Pager for PageCacheManager {
         let page = CachePage::alloc_zero()?;
         Ok(self.pages.lock().get_or_insert(idx, || page).clone().into())
     }
+
+    fn write_back(&self, idx_range: Range<usize>) -> Result<()> {
+        self.evict_range(idx_range.start * PAGE_SIZE..idx_range.end * PAGE_SIZE)
+    }
 }
 
 /// A page in the page cache.
@@ -578,6 +634,18 @@ pub trait PageCacheBackend: Sync + Send {
     fn write_page_async(&self, idx: usize, frame: &CachePage) -> Result<BioWaiter>;
     /// Returns the number of pages in the backend.
     fn npages(&self) -> usize;
+    /// Returns the frame that backs the page at `idx`, if the backend supports direct access.
+    ///
+    /// Such a page bypasses the page cache, so the users of the page cache read and write
+    /// the backend directly, e.g., a file on a persistent memory device can be mapped
+    /// into user space without copies.
+    fn direct_access(&self, _idx: usize) -> Option<UFrame> {
+        None
+    }
+    /// Persists the writes to the pages returned by `direct_access`.
+    fn flush_direct(&self) -> Result<()> {
+        Ok(())
+    }
 }
 
 impl dyn PageCacheBackend {
@@ -0,0 +1,66 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! The integrity verification of the block devices, which is configured by the kernel
+//! command line.
+//!
+//! For example, `verity.data=vext2 verity.hash=vext2hash verity.root_hash=<hex>` verifies the
+//! blocks of the device `vext2` against the hash tree on the device `vext2hash`, which is
+//! created by `veritysetup format` along with the root hash. The verified device is read-only.
+
+use aster_block::{verity::VerityDevice, BlockDevice};
+use ostd::boot::boot_info;
+
+use super::start_block_device;
+use crate::{
+    kcmdline::{KCmdlineArg, ModuleArg},
+    prelude::*,
+};
+
+/// Wraps the block device to verify its blocks if it is specified by the kernel command line.
+///
+/// This method fails if the verification is specified but cannot be set up, in which case
+/// the device must not be used.
+pub(super) fn verify_if_specified(
+    device_name: &str,
+    device: Arc<dyn BlockDevice>,
+) -> Result<Arc<dyn BlockDevice>> {
+    let karg: KCmdlineArg = boot_info().kernel_cmdline.as_str().into();
+    let Some(args) = karg.get_module_args("verity") else {
+        return Ok(device);
+    };
+    if get_arg(args, "data") != Some(device_name) {
+        return Ok(device);
+    }
+
+    let Some(hash_device_name) = get_arg(args, "hash") else {
+        return_errno_with_message!(Errno::EINVAL, "the hash device is not specified");
+    };
+    let Some(root_digest) = get_arg(args, "root_hash").and_then(parse_hex) else {
+        return_errno_with_message!(Errno::EINVAL, "the root hash is not specified or invalid");
+    };
+
+    let hash_device = start_block_device(hash_device_name)?;
+    let verity_device = VerityDevice::open(device, hash_device, &root_digest)?;
+    info!(
+        "[kernel] Verify {} with the hash tree on {}",
+        device_name, hash_device_name
+    );
+    Ok(Arc::new(verity_device))
+}
+
+fn get_arg<'a>(args: &'a [ModuleArg], name: &str) -> Option<&'a str> {
+    args.iter().find_map(|arg| match arg {
+        ModuleArg::KeyVal(key, value) if key.as_bytes() == name.as_bytes() => value.to_str().ok(),
+        _ => None,
+    })
+}
+
+fn parse_hex(hex: &str) -> Option<Vec<u8>> {
+    if hex.len() % 2 != 0 {
+        return None;
+    }
+    (0..hex.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(hex.get(i..i + 2)?, 16).ok())
+        .collect()
+}
@@ -0,0 +1,146 @@
+// SPDX-License-Identifier: MPL-2.0
+
+use alloc::collections::btree_map::BTreeMap;
+
+use aster_rights::ReadOp;
+use id_alloc::IdAlloc;
+
+use super::{key_t, IpcFlags, IpcPermission, IPC_PRIVATE};
+use crate::{prelude::*, process::Credentials};
+
+/// A System V IPC object.
+pub(super) trait IpcObject {
+    fn permission(&self) -> &IpcPermission;
+
+    /// Returns whether the object is removed but still in use.
+    ///
+    /// Such an object cannot be found by its key.
+    fn is_removed(&self) -> bool {
+        false
+    }
+}
+
+/// The IPC objects of one kind in an IPC namespace, indexed by their IDs.
+pub(super) struct IpcIds<T> {
+    inner: Mutex<IpcIdsInner<T>>,
+}
+
+struct IpcIdsInner<T> {
+    id_allocator: IdAlloc,
+    objects: BTreeMap<key_t, Arc<T>>,
+}
+
+impl<T: IpcObject> IpcIds<T> {
+    /// Creates an empty set of IPC objects with at most `capacity` objects.
+    pub(super) fn new(capacity: usize) -> Self {
+        Self {
+            inner: Mutex::new(IpcIdsInner {
+                id_allocator: IdAlloc::with_capacity(capacity),
+                objects: BTreeMap::new(),
+            }),
+        }
+    }
+
+    /// Gets the ID of the object with the key, or creates a new object if necessary.
+    ///
+    /// This implements the common logic of `shmget` and `msgget`. The `check` closure is called
+    /// on the existing object with the key, and the `create` closure is called with the ID of
+    /// the new object.
+    pub(super) fn get_or_create<C, F>(
+        &self,
+        key: key_t,
+        flags: i32,
+        credentials: &Credentials<ReadOp>,
+        check: C,
+        create: F,
+    ) -> Result<key_t>
+    where
+        C: FnOnce(&T) -> Result<()>,
+        F: FnOnce(key_t) -> Result<T>,
+    {
+        let ipc_flags = IpcFlags::from_bits_truncate(flags as u32);
+        let mode = (flags & 0o777) as u16;
+
+        let mut inner = self.inner.lock();
+
+        if key != IPC_PRIVATE
+            && let Some((id, object)) = inner
+                .objects
+                .iter()
+                .find(|(_, object)| !object.is_removed() && object.permission().key() == key)
+        {
+            if ipc_flags.contains(IpcFlags::IPC_CREAT | IpcFlags::IPC_EXCL) {
+                return_errno_with_message!(Errno::EEXIST, "the IPC object already exists");
+            }
+            object.permission().check(credentials, mode)?;
+            check(object)?;
+            return Ok(*id);
+        }
+
+        if key != IPC_PRIVATE && !ipc_flags.contains(IpcFlags::IPC_CREAT) {
+            return_errno_with_message!(Errno::ENOENT, "the IPC object does not exist");
+        }
+
+        let Some(id) = inner.id_allocator.alloc() else {
+            return_errno_with_message!(Errno::ENOSPC, "there are too many IPC objects");
+        };
+        let id = id as key_t;
+        match create(id) {
+            Ok(object) => {
+                inner.objects.insert(id, Arc::new(object));
+                Ok(id)
+            }
+            Err(err) => {
+                inner.id_allocator.free(id as usize);
+                Err(err)
+            }
+        }
+    }
+
+    /// Gets the object with the ID.
+    pub(super) fn get(&self, id: key_t) -> Result<Arc<T>> {
+        self.inner
+            .lock()
+            .objects
+            .get(&id)
+            .cloned()
+            .ok_or_else(|| Error::with_message(Errno::EINVAL, "the IPC object does not exist"))
+    }
+
+    /// Finds an object that satisfies the predicate.
+    pub(super) fn find<F>(&self, mut pred: F) -> Option<Arc<T>>
+    where
+        F: FnMut(&T) -> bool,
+    {
+        self.inner
+            .lock()
+            .objects
+            .values()
+            .find(|object| pred(object))
+            .cloned()
+    }
+
+    /// Removes the object with the ID.
+    pub(super) fn remove(&self, id: key_t) {
+        let mut inner = self.inner.lock();
+        if inner.objects.remove(&id).is_some() {
+            inner.id_allocator.free(id as usize);
+        }
+    }
+
+    /// Removes the objects that satisfy the predicate.
+    pub(super) fn remove_if<F>(&self, mut pred: F)
+    where
+        F: FnMut(&T) -> bool,
+    {
+        let inner = &mut *self.inner.lock();
+        inner.objects.retain(|id, object| {
+            if pred(object) {
+                inner.id_allocator.free(*id as usize);
+                false
+            } else {
+                true
+            }
+        });
+    }
+}
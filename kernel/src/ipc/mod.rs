@@ -1,15 +1,27 @@
 // SPDX-License-Identifier: MPL-2.0
 
+use aster_rights::ReadOp;
+
 use crate::{
     prelude::*,
-    process::{Gid, Uid},
+    process::{credentials::capabilities::CapSet, Credentials, Gid, Uid},
 };
 
+mod ids;
+pub mod mqueue;
+pub mod msg;
+mod namespace;
 pub mod semaphore;
+pub mod shm;
+
+pub use self::namespace::IpcNamespace;
 
 #[allow(non_camel_case_types)]
 pub type key_t = i32;
 
+/// The key to create a new IPC object that cannot be found by key.
+pub const IPC_PRIVATE: key_t = 0;
+
 bitflags! {
     pub struct IpcFlags: u32{
         /// Create key if key does not exist
@@ -86,7 +98,45 @@ impl IpcPermission {
         self.mode
     }
 
-    pub(self) fn new_sem_perm(key: key_t, uid: Uid, gid: Gid, mode: u16) -> Self {
+    /// Checks whether the credentials are granted the permissions in `flag`.
+    ///
+    /// The permission bits in `flag` are interpreted like the ones of files, and any of the
+    /// owner, group, or other bits requests the permission. For example, `0o400` and `0o004`
+    /// both request the read permission.
+    pub fn check(&self, credentials: &Credentials<ReadOp>, flag: u16) -> Result<()> {
+        let requested = (flag >> 6) | (flag >> 3) | flag;
+
+        let euid = credentials.euid();
+        let egid = credentials.egid();
+        let granted = if euid == self.uid || euid == self.cuid {
+            self.mode >> 6
+        } else if egid == self.gid
+            || egid == self.cguid
+            || credentials.groups().contains(&self.gid)
+            || credentials.groups().contains(&self.cguid)
+        {
+            self.mode >> 3
+        } else {
+            self.mode
+        };
+
+        if requested & !granted & 0o7 != 0
+            && !credentials.effective_capset().contains(CapSet::IPC_OWNER)
+        {
+            return_errno_with_message!(Errno::EACCES, "the IPC permission is denied");
+        }
+        Ok(())
+    }
+
+    /// Returns whether the credentials own the IPC object, i.e., can control and remove it.
+    pub fn is_owned_by(&self, credentials: &Credentials<ReadOp>) -> bool {
+        let euid = credentials.euid();
+        euid == self.uid
+            || euid == self.cuid
+            || credentials.effective_capset().contains(CapSet::SYS_ADMIN)
+    }
+
+    pub(self) fn new(key: key_t, uid: Uid, gid: Gid, mode: u16) -> Self {
         Self {
             key,
             uid,
@@ -96,8 +146,33 @@ impl IpcPermission {
             mode,
         }
     }
+
+    pub(self) fn to_c(&self) -> ipc64_perm {
+        ipc64_perm {
+            key: self.key,
+            uid: self.uid.into(),
+            gid: self.gid.into(),
+            cuid: self.cuid.into(),
+            cgid: self.cguid.into(),
+            mode: u32::from(self.mode),
+            ..Default::default()
+        }
+    }
 }
 
-pub(super) fn init() {
-    semaphore::init();
+/// The permissions of an IPC object, which are reported by the `IPC_STAT` commands.
+#[repr(C)]
+#[derive(Debug, Default, Clone, Copy, Pod)]
+pub struct ipc64_perm {
+    key: key_t,
+    uid: u32,
+    gid: u32,
+    cuid: u32,
+    cgid: u32,
+    mode: u32,
+    seq: u16,
+    __pad2: u16,
+    __pad3: u32,
+    __unused1: u64,
+    __unused2: u64,
 }
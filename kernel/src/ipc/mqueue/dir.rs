@@ -0,0 +1,217 @@
+// SPDX-License-Identifier: MPL-2.0
+
+use core::time::Duration;
+
+use super::{queue::QueueAttr, MessageQueue, MqueueFs, BLOCK_SIZE, ROOT_INO};
+use crate::{
+    fs::utils::{DirentVisitor, FileSystem, Inode, InodeMode, InodeType, Metadata, MknodType},
+    prelude::*,
+    process::{credentials::capabilities::CapSet, posix_thread::AsPosixThread, Gid, Uid},
+};
+
+/// The root directory of the message queue filesystem, which contains all the queues.
+pub(super) struct MqueueDir {
+    queues: Mutex<BTreeMap<String, Arc<MessageQueue>>>,
+    metadata: RwLock<Metadata>,
+    this: Weak<MqueueDir>,
+    fs: Weak<MqueueFs>,
+}
+
+impl MqueueDir {
+    pub(super) fn new(fs: Weak<MqueueFs>) -> Arc<Self> {
+        Arc::new_cyclic(|this| Self {
+            queues: Mutex::new(BTreeMap::new()),
+            // Like `/tmp`, everyone can create queues but only the owners can remove them.
+            metadata: RwLock::new(Metadata::new_dir(
+                ROOT_INO,
+                InodeMode::from_bits_truncate(0o1777),
+                BLOCK_SIZE,
+            )),
+            this: this.clone(),
+            fs,
+        })
+    }
+
+    /// Creates a queue with the attributes.
+    pub(super) fn create_queue(
+        &self,
+        name: &str,
+        mode: InodeMode,
+        attr: QueueAttr,
+    ) -> Result<Arc<MessageQueue>> {
+        let mut queues = self.queues.lock();
+        if queues.contains_key(name) {
+            return_errno_with_message!(Errno::EEXIST, "the queue already exists");
+        }
+
+        let queue = MessageQueue::new(attr, mode, &self.fs.upgrade().unwrap())?;
+        queues.insert(name.to_string(), queue.clone());
+
+        Ok(queue)
+    }
+
+    fn this(&self) -> Arc<MqueueDir> {
+        self.this.upgrade().unwrap()
+    }
+}
+
+impl Inode for MqueueDir {
+    fn size(&self) -> usize {
+        0
+    }
+
+    fn resize(&self, _new_size: usize) -> Result<()> {
+        Err(Error::new(Errno::EISDIR))
+    }
+
+    fn metadata(&self) -> Metadata {
+        *self.metadata.read()
+    }
+
+    fn ino(&self) -> u64 {
+        self.metadata.read().ino
+    }
+
+    fn type_(&self) -> InodeType {
+        InodeType::Dir
+    }
+
+    fn mode(&self) -> Result<InodeMode> {
+        Ok(self.metadata.read().mode)
+    }
+
+    fn set_mode(&self, mode: InodeMode) -> Result<()> {
+        self.metadata.write().mode = mode;
+        Ok(())
+    }
+
+    fn owner(&self) -> Result<Uid> {
+        Ok(self.metadata.read().uid)
+    }
+
+    fn set_owner(&self, uid: Uid) -> Result<()> {
+        self.metadata.write().uid = uid;
+        Ok(())
+    }
+
+    fn group(&self) -> Result<Gid> {
+        Ok(self.metadata.read().gid)
+    }
+
+    fn set_group(&self, gid: Gid) -> Result<()> {
+        self.metadata.write().gid = gid;
+        Ok(())
+    }
+
+    fn atime(&self) -> Duration {
+        self.metadata.read().atime
+    }
+
+    fn set_atime(&self, time: Duration) {
+        self.metadata.write().atime = time;
+    }
+
+    fn mtime(&self) -> Duration {
+        self.metadata.read().mtime
+    }
+
+    fn set_mtime(&self, time: Duration) {
+        self.metadata.write().mtime = time;
+    }
+
+    fn ctime(&self) -> Duration {
+        self.metadata.read().ctime
+    }
+
+    fn set_ctime(&self, time: Duration) {
+        self.metadata.write().ctime = time;
+    }
+
+    fn create(&self, name: &str, type_: InodeType, mode: InodeMode) -> Result<Arc<dyn Inode>> {
+        if type_ != InodeType::File {
+            return_errno_with_message!(Errno::EPERM, "only message queues can be created");
+        }
+
+        let queue = self.create_queue(name, mode, QueueAttr::new_default())?;
+        Ok(queue)
+    }
+
+    fn mknod(&self, _name: &str, _mode: InodeMode, _type_: MknodType) -> Result<Arc<dyn Inode>> {
+        Err(Error::new(Errno::EPERM))
+    }
+
+    fn readdir_at(&self, offset: usize, visitor: &mut dyn DirentVisitor) -> Result<usize> {
+        let mut entries: Vec<(String, u64, InodeType)> = vec![
+            (String::from("."), self.ino(), InodeType::Dir),
+            (String::from(".."), self.ino(), InodeType::Dir),
+        ];
+        entries.extend(
+            self.queues
+                .lock()
+                .iter()
+                .map(|(name, queue)| (name.clone(), queue.ino(), InodeType::File)),
+        );
+
+        let mut iterate_offset = offset;
+        for (name, ino, type_) in entries.iter().skip(offset) {
+            if let Err(err) = visitor.visit(name, *ino, *type_, iterate_offset) {
+                if iterate_offset == offset {
+                    return Err(err);
+                }
+                break;
+            }
+            iterate_offset += 1;
+        }
+
+        Ok(iterate_offset - offset)
+    }
+
+    fn link(&self, _old: &Arc<dyn Inode>, _name: &str) -> Result<()> {
+        Err(Error::new(Errno::EPERM))
+    }
+
+    fn unlink(&self, name: &str) -> Result<()> {
+        let mut queues = self.queues.lock();
+        let Some(queue) = queues.get(name) else {
+            return_errno_with_message!(Errno::ENOENT, "the queue does not exist");
+        };
+
+        // The directory is sticky, so only the owner of the queue or the directory can remove
+        // the queue.
+        let credentials = current_thread!().as_posix_thread().unwrap().credentials();
+        let fsuid = credentials.fsuid();
+        if fsuid != queue.owner()?
+            && fsuid != self.owner()?
+            && !credentials.effective_capset().contains(CapSet::FOWNER)
+        {
+            return_errno_with_message!(Errno::EPERM, "the queue is not owned by the caller");
+        }
+
+        queues.remove(name);
+        Ok(())
+    }
+
+    fn rmdir(&self, _name: &str) -> Result<()> {
+        Err(Error::new(Errno::EPERM))
+    }
+
+    fn lookup(&self, name: &str) -> Result<Arc<dyn Inode>> {
+        if name == "." || name == ".." {
+            return Ok(self.this());
+        }
+
+        self.queues
+            .lock()
+            .get(name)
+            .map(|queue| queue.clone() as Arc<dyn Inode>)
+            .ok_or_else(|| Error::with_message(Errno::ENOENT, "the queue does not exist"))
+    }
+
+    fn rename(&self, _old_name: &str, _target: &Arc<dyn Inode>, _new_name: &str) -> Result<()> {
+        Err(Error::new(Errno::EPERM))
+    }
+
+    fn fs(&self) -> Arc<dyn FileSystem> {
+        self.fs.upgrade().unwrap()
+    }
+}
@@ -0,0 +1,205 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! POSIX message queues.
+//!
+//! Each IPC namespace has a message queue filesystem (mqueue), whose root directory contains all
+//! the message queues in the namespace. The queues are opened by `mq_open` via an internal mount
+//! of the filesystem, so the queue descriptors are ordinary file descriptors that can be polled.
+//! The filesystem can also be mounted by users (e.g., at `/dev/mqueue`) to list, inspect, and
+//! remove the queues.
+//!
+//! Like Linux, the number of queues in a namespace and the sizes of the messages are limited by
+//! the default values of `/proc/sys/fs/mqueue`, while the memory used by the queues of each user
+//! is limited by `RLIMIT_MSGQUEUE`.
+
+use core::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+
+pub use self::queue::{
+    mq_attr, MessageQueue, Notification, NotifyKind, MQ_PRIO_MAX, NOTIFY_COOKIE_LEN,
+};
+use self::{dir::MqueueDir, queue::QueueAttr};
+use crate::{
+    fs::{
+        inode_handle::InodeHandle,
+        path::{Dentry, MountNode},
+        utils::{
+            AccessMode, CreationFlags, FileSystem, FsFlags, Inode, InodeMode, StatusFlags,
+            SuperBlock, NAME_MAX,
+        },
+    },
+    prelude::*,
+    process::{credentials::capabilities::CapSet, posix_thread::AsPosixThread},
+};
+
+mod dir;
+mod queue;
+
+const MQUEUE_MAGIC: u64 = 0x1980_0202;
+const BLOCK_SIZE: usize = PAGE_SIZE;
+const ROOT_INO: u64 = 1;
+
+// The following constant values are derived from the default values in Linux.
+
+/// Maximum number of queues in an IPC namespace.
+const QUEUES_MAX: usize = 256;
+/// Maximum number of messages in a queue, unless the creator has `CAP_SYS_RESOURCE`.
+const MSG_MAX: usize = 10;
+/// Maximum size of a message, unless the creator has `CAP_SYS_RESOURCE`.
+const MSGSIZE_MAX: usize = 8192;
+/// Default number of messages in a queue if no attributes are specified.
+const MSG_DEFAULT: usize = 10;
+/// Default size of a message if no attributes are specified.
+const MSGSIZE_DEFAULT: usize = 8192;
+/// Hard limit of the number of messages in a queue.
+const HARD_MSGMAX: usize = 65536;
+/// Hard limit of the size of a message.
+const HARD_MSGSIZEMAX: usize = 16 * 1024 * 1024;
+
+/// The message queue filesystem of an IPC namespace.
+pub struct MqueueFs {
+    sb: SuperBlock,
+    root: Arc<MqueueDir>,
+    /// The number of queues that are not destroyed yet, including the unlinked ones.
+    num_queues: AtomicUsize,
+}
+
+impl MqueueFs {
+    fn new() -> Arc<Self> {
+        Arc::new_cyclic(|weak_fs| Self {
+            sb: SuperBlock::new(MQUEUE_MAGIC, BLOCK_SIZE, NAME_MAX),
+            root: MqueueDir::new(weak_fs.clone()),
+            num_queues: AtomicUsize::new(0),
+        })
+    }
+}
+
+fn alloc_ino() -> u64 {
+    static NEXT_INO: AtomicU64 = AtomicU64::new(ROOT_INO + 1);
+
+    NEXT_INO.fetch_add(1, Ordering::Relaxed)
+}
+
+impl FileSystem for MqueueFs {
+    fn sync(&self) -> Result<()> {
+        Ok(())
+    }
+
+    fn root_inode(&self) -> Arc<dyn Inode> {
+        self.root.clone()
+    }
+
+    fn sb(&self) -> SuperBlock {
+        self.sb.clone()
+    }
+
+    fn flags(&self) -> FsFlags {
+        FsFlags::empty()
+    }
+
+    fn name(&self) -> &'static str {
+        "mqueue"
+    }
+}
+
+/// The POSIX message queues in an IPC namespace.
+pub struct PosixMessageQueues {
+    fs: Arc<MqueueFs>,
+    /// The internal mount, through which the queues are opened by `mq_open`.
+    mount: Arc<MountNode>,
+}
+
+impl PosixMessageQueues {
+    pub(super) fn new() -> Self {
+        let fs = MqueueFs::new();
+        let mount = MountNode::new_root(fs.clone());
+
+        Self { fs, mount }
+    }
+
+    /// Returns the message queue filesystem, which is used when users mount `mqueue`.
+    pub fn fs(&self) -> &Arc<MqueueFs> {
+        &self.fs
+    }
+
+    /// Opens the queue with the name, or creates a new queue if `O_CREAT` is specified.
+    ///
+    /// If `attr` is `None`, a new queue is created with the default attributes.
+    pub fn open(
+        &self,
+        name: &str,
+        flags: u32,
+        mode: u16,
+        attr: Option<&mq_attr>,
+    ) -> Result<InodeHandle> {
+        check_name(name)?;
+
+        let access_mode = AccessMode::from_u32(flags)?;
+        let creation_flags = CreationFlags::from_bits_truncate(flags);
+        let status_flags = StatusFlags::from_bits_truncate(flags) & StatusFlags::O_NONBLOCK;
+
+        let root = Dentry::new_fs_root(self.mount.clone());
+        match root.lookup(name) {
+            Ok(_) if creation_flags.contains(CreationFlags::O_CREAT | CreationFlags::O_EXCL) => {
+                return_errno_with_message!(Errno::EEXIST, "the queue already exists");
+            }
+            Ok(dentry) => return InodeHandle::new(dentry, access_mode, status_flags),
+            Err(err) if err.error() != Errno::ENOENT => return Err(err),
+            Err(_) if !creation_flags.contains(CreationFlags::O_CREAT) => {
+                return_errno_with_message!(Errno::ENOENT, "the queue does not exist");
+            }
+            Err(_) => (),
+        }
+
+        let attr = match attr {
+            Some(attr) => QueueAttr::from_c(attr)?,
+            None => QueueAttr::new_default(),
+        };
+        let mode = InodeMode::from_bits_truncate(mode);
+        match self.fs.root.create_queue(name, mode, attr) {
+            Ok(_) => {
+                let dentry = root.lookup(name)?;
+                // Like other files, the access mode is not checked for the newly created queue.
+                InodeHandle::new_unchecked_access(dentry, access_mode, status_flags)
+            }
+            // Another process may create the queue at the same time.
+            Err(err)
+                if err.error() == Errno::EEXIST
+                    && !creation_flags.contains(CreationFlags::O_EXCL) =>
+            {
+                InodeHandle::new(root.lookup(name)?, access_mode, status_flags)
+            }
+            Err(err) => Err(err),
+        }
+    }
+
+    /// Removes the queue with the name.
+    ///
+    /// The queue is destroyed after all its descriptors are closed.
+    pub fn unlink(&self, name: &str) -> Result<()> {
+        check_name(name)?;
+
+        let root = Dentry::new_fs_root(self.mount.clone());
+        root.unlink(name)
+    }
+}
+
+/// Checks whether the name is a valid queue name.
+///
+/// The leading slash of the name is removed by the C library, so the name cannot contain any
+/// slashes.
+fn check_name(name: &str) -> Result<()> {
+    if name.len() > NAME_MAX {
+        return_errno_with_message!(Errno::ENAMETOOLONG, "the queue name is too long");
+    }
+    if name.is_empty() || name == "." || name == ".." || name.contains('/') {
+        return_errno_with_message!(Errno::EACCES, "the queue name is invalid");
+    }
+    Ok(())
+}
+
+fn has_sys_resource_cap() -> bool {
+    let credentials = current_thread!().as_posix_thread().unwrap().credentials();
+    credentials
+        .effective_capset()
+        .contains(CapSet::SYS_RESOURCE)
+}
@@ -0,0 +1,638 @@
+// SPDX-License-Identifier: MPL-2.0
+
+use alloc::collections::{btree_map::BTreeMap, VecDeque};
+use core::{fmt::Write, sync::atomic::Ordering, time::Duration};
+
+use ostd::sync::WaitQueue;
+
+use super::{
+    alloc_ino, has_sys_resource_cap, MqueueFs, BLOCK_SIZE, HARD_MSGMAX, HARD_MSGSIZEMAX,
+    MSGSIZE_DEFAULT, MSGSIZE_MAX, MSG_DEFAULT, MSG_MAX, QUEUES_MAX,
+};
+use crate::{
+    events::IoEvents,
+    fs::{
+        file_handle::FileLike,
+        utils::{FileSystem, Inode, InodeMode, InodeType, Metadata},
+    },
+    net::socket::netlink::NetlinkSocket,
+    prelude::*,
+    process::{
+        posix_thread::AsPosixThread,
+        signal::{
+            c_types::{siginfo_t, sigval_t},
+            constants::SI_MESGQ,
+            sig_num::SigNum,
+            signals::user::UserInfoSignal,
+            PollHandle, Pollee,
+        },
+        Gid, Pid, Process, ResourceType, Uid,
+    },
+    time::{clocks::RealTimeCoarseClock, wait::ManagedTimeout},
+};
+
+/// The maximum priority of messages (exclusive).
+pub const MQ_PRIO_MAX: u32 = 32768;
+
+/// The length of the cookie that is sent to the netlink socket for `SIGEV_THREAD`.
+pub const NOTIFY_COOKIE_LEN: usize = 32;
+
+/// The size reported by `stat`, which is the size of the status line in Linux.
+const FILENT_SIZE: usize = 80;
+
+/// The bytes of the queues charged to each user (indexed by the real user ID), which are limited
+/// by `RLIMIT_MSGQUEUE`.
+static USER_MQ_BYTES: Mutex<BTreeMap<u32, u64>> = Mutex::new(BTreeMap::new());
+
+/// The attributes of a queue, which are fixed after the queue is created.
+#[derive(Debug, Clone, Copy)]
+pub(super) struct QueueAttr {
+    max_msgs: usize,
+    msg_size: usize,
+}
+
+impl QueueAttr {
+    pub(super) fn new_default() -> Self {
+        Self {
+            max_msgs: MSG_DEFAULT.min(MSG_MAX),
+            msg_size: MSGSIZE_DEFAULT.min(MSGSIZE_MAX),
+        }
+    }
+
+    /// Validates the attributes specified by `mq_open`.
+    pub(super) fn from_c(attr: &mq_attr) -> Result<Self> {
+        if attr.mq_maxmsg <= 0 || attr.mq_msgsize <= 0 {
+            return_errno_with_message!(Errno::EINVAL, "the attributes must be positive");
+        }
+
+        let (max_msgs, msg_size) = (attr.mq_maxmsg as usize, attr.mq_msgsize as usize);
+        let (max_msgs_limit, msg_size_limit) = if has_sys_resource_cap() {
+            (HARD_MSGMAX, HARD_MSGSIZEMAX)
+        } else {
+            (MSG_MAX, MSGSIZE_MAX)
+        };
+        if max_msgs > max_msgs_limit || msg_size > msg_size_limit {
+            return_errno_with_message!(Errno::EINVAL, "the attributes exceed the limits");
+        }
+
+        Ok(Self { max_msgs, msg_size })
+    }
+
+    /// Returns the number of bytes charged to the creator of the queue.
+    ///
+    /// Like Linux, the maximum size of all messages and the bookkeeping overhead are charged when
+    /// the queue is created, regardless of the messages actually sent.
+    fn num_charged_bytes(&self) -> u64 {
+        let overhead = size_of::<Box<[u8]>>() + size_of::<usize>() * 2;
+        (self.max_msgs * (self.msg_size + overhead)) as u64
+    }
+}
+
+/// A POSIX message queue, which is also an inode in the message queue filesystem.
+pub struct MessageQueue {
+    attr: QueueAttr,
+    inner: Mutex<MessageQueueInner>,
+    /// The queue of the senders waiting for the queue to be not full.
+    send_wait_queue: WaitQueue,
+    /// The queue of the receivers waiting for the queue to be not empty.
+    recv_wait_queue: WaitQueue,
+    pollee: Pollee,
+    metadata: RwLock<Metadata>,
+    /// The real user ID of the creator, who is charged for the queue.
+    charged_uid: Uid,
+    fs: Weak<MqueueFs>,
+}
+
+struct MessageQueueInner {
+    /// The messages grouped by the priorities.
+    ///
+    /// The messages with the highest priority are received first, and the messages with the same
+    /// priority are received in the order they are sent.
+    messages: BTreeMap<u32, VecDeque<Box<[u8]>>>,
+    num_msgs: usize,
+    /// The number of bytes of the messages in the queue.
+    num_bytes: usize,
+    num_waiting_receivers: usize,
+    notification: Option<Notification>,
+}
+
+impl MessageQueue {
+    /// Creates a queue owned by the current thread.
+    ///
+    /// This method fails if the namespace has too many queues, or if the memory charged to the
+    /// current user exceeds `RLIMIT_MSGQUEUE`.
+    pub(super) fn new(attr: QueueAttr, mode: InodeMode, fs: &Arc<MqueueFs>) -> Result<Arc<Self>> {
+        let current = current_thread!();
+        let posix_thread = current.as_posix_thread().unwrap();
+        let credentials = posix_thread.credentials();
+
+        let num_queues = fs.num_queues.fetch_add(1, Ordering::Relaxed);
+        if num_queues >= QUEUES_MAX && !has_sys_resource_cap() {
+            fs.num_queues.fetch_sub(1, Ordering::Relaxed);
+            return_errno_with_message!(Errno::ENOSPC, "too many message queues");
+        }
+
+        let charged_uid = credentials.ruid();
+        let limit = posix_thread
+            .process()
+            .resource_limits()
+            .lock()
+            .get_rlimit(ResourceType::RLIMIT_MSGQUEUE)
+            .get_cur();
+        if let Err(err) = charge_user(charged_uid, attr.num_charged_bytes(), limit) {
+            fs.num_queues.fetch_sub(1, Ordering::Relaxed);
+            return Err(err);
+        }
+
+        let mut metadata = Metadata::new_file(alloc_ino(), mode, BLOCK_SIZE);
+        metadata.size = FILENT_SIZE;
+        metadata.uid = credentials.fsuid();
+        metadata.gid = credentials.fsgid();
+
+        Ok(Arc::new(Self {
+            attr,
+            inner: Mutex::new(MessageQueueInner {
+                messages: BTreeMap::new(),
+                num_msgs: 0,
+                num_bytes: 0,
+                num_waiting_receivers: 0,
+                notification: None,
+            }),
+            send_wait_queue: WaitQueue::new(),
+            recv_wait_queue: WaitQueue::new(),
+            pollee: Pollee::new(),
+            metadata: RwLock::new(metadata),
+            charged_uid,
+            fs: Arc::downgrade(fs),
+        }))
+    }
+
+    /// Returns the attributes of the queue for `mq_getsetattr`.
+    ///
+    /// The flags of the queue descriptor are not included.
+    pub fn attr(&self) -> mq_attr {
+        mq_attr {
+            mq_maxmsg: self.attr.max_msgs as i64,
+            mq_msgsize: self.attr.msg_size as i64,
+            mq_curmsgs: self.inner.lock().num_msgs as i64,
+            ..Default::default()
+        }
+    }
+
+    /// Returns the maximum size of a message.
+    pub fn msg_size(&self) -> usize {
+        self.attr.msg_size
+    }
+
+    /// Sends a message with the priority to the queue.
+    ///
+    /// If the queue is full, this method waits until there is enough space or the timeout is
+    /// reached, unless `is_nonblocking` is true.
+    pub fn send(
+        &self,
+        text: Box<[u8]>,
+        priority: u32,
+        is_nonblocking: bool,
+        timeout: Option<ManagedTimeout>,
+        ctx: &Context,
+    ) -> Result<()> {
+        if priority >= MQ_PRIO_MAX {
+            return_errno_with_message!(Errno::EINVAL, "the priority is too large");
+        }
+        if text.len() > self.attr.msg_size {
+            return_errno_with_message!(Errno::EMSGSIZE, "the message is too long");
+        }
+
+        let mut text = Some(text);
+        let mut try_send = || self.try_send(&mut text, priority, ctx);
+        if is_nonblocking {
+            try_send()
+                .ok_or_else(|| Error::with_message(Errno::EAGAIN, "the message queue is full"))
+        } else {
+            self.send_wait_queue
+                .pause_until_or_timeout(try_send, timeout)
+        }
+    }
+
+    fn try_send(&self, text: &mut Option<Box<[u8]>>, priority: u32, ctx: &Context) -> Option<()> {
+        let mut inner = self.inner.lock();
+        if inner.num_msgs >= self.attr.max_msgs {
+            return None;
+        }
+
+        let text = text.take().unwrap();
+        inner.num_msgs += 1;
+        inner.num_bytes += text.len();
+        inner.messages.entry(priority).or_default().push_back(text);
+
+        // Like Linux, the notification is only triggered if the queue becomes non-empty while no
+        // receivers are waiting for the message.
+        if inner.num_msgs == 1
+            && inner.num_waiting_receivers == 0
+            && let Some(notification) = inner.notification.take()
+        {
+            notification.notify(ctx);
+        }
+        drop(inner);
+
+        self.pollee.notify(IoEvents::IN);
+        self.recv_wait_queue.wake_all();
+        self.touch_mtime();
+
+        Some(())
+    }
+
+    /// Receives the oldest message with the highest priority from the queue.
+    ///
+    /// If the queue is empty, this method waits until a message is sent or the timeout is
+    /// reached, unless `is_nonblocking` is true.
+    pub fn receive(
+        &self,
+        max_len: usize,
+        is_nonblocking: bool,
+        timeout: Option<ManagedTimeout>,
+    ) -> Result<(Box<[u8]>, u32)> {
+        if max_len < self.attr.msg_size {
+            return_errno_with_message!(Errno::EMSGSIZE, "the buffer is too small");
+        }
+
+        if is_nonblocking {
+            return self
+                .try_receive()
+                .ok_or_else(|| Error::with_message(Errno::EAGAIN, "the message queue is empty"));
+        }
+
+        self.inner.lock().num_waiting_receivers += 1;
+        let res = self
+            .recv_wait_queue
+            .pause_until_or_timeout(|| self.try_receive(), timeout);
+        self.inner.lock().num_waiting_receivers -= 1;
+
+        res
+    }
+
+    fn try_receive(&self) -> Option<(Box<[u8]>, u32)> {
+        let mut inner = self.inner.lock();
+
+        let mut entry = inner.messages.last_entry()?;
+        let priority = *entry.key();
+        let text = entry.get_mut().pop_front().unwrap();
+        if entry.get().is_empty() {
+            entry.remove();
+        }
+        inner.num_msgs -= 1;
+        inner.num_bytes -= text.len();
+        drop(inner);
+
+        self.pollee.notify(IoEvents::OUT);
+        self.send_wait_queue.wake_all();
+        self.touch_mtime();
+
+        Some((text, priority))
+    }
+
+    /// Registers the notification for `mq_notify`, or removes the notification of the current
+    /// process if `notification` is `None`.
+    pub fn set_notification(
+        &self,
+        notification: Option<Notification>,
+        ctx: &Context,
+    ) -> Result<()> {
+        let mut inner = self.inner.lock();
+
+        let Some(notification) = notification else {
+            if inner
+                .notification
+                .as_ref()
+                .is_some_and(|old| old.owner_pid == ctx.process.pid())
+            {
+                inner.notification.take().unwrap().remove();
+            }
+            return Ok(());
+        };
+
+        if inner.notification.is_some() {
+            return_errno_with_message!(Errno::EBUSY, "the notification is already registered");
+        }
+        inner.notification = Some(notification);
+
+        Ok(())
+    }
+
+    /// Removes the notification if it is registered by the current process.
+    ///
+    /// This method should be called when a descriptor of the queue is closed, since the
+    /// notification is bound to the descriptors of the registering process.
+    pub fn flush(&self) {
+        let mut inner = self.inner.lock();
+
+        if inner
+            .notification
+            .as_ref()
+            .is_some_and(|notification| notification.owner_pid == current!().pid())
+        {
+            inner.notification.take().unwrap().remove();
+        }
+    }
+
+    /// Returns the status line that is read from the queue file.
+    fn status(&self) -> String {
+        let inner = self.inner.lock();
+
+        let (notify, signo, pid) = match inner.notification.as_ref() {
+            Some(notification) => {
+                let pid = current!()
+                    .pid_ns()
+                    .local_id(notification.owner_pid)
+                    .unwrap_or(0);
+                (
+                    notification.kind.sigev_notify(),
+                    notification.kind.signo(),
+                    pid,
+                )
+            }
+            None => (0, 0, 0),
+        };
+
+        let mut status = String::new();
+        let _ = writeln!(
+            status,
+            "QSIZE:{:<10} NOTIFY:{:<5} SIGNO:{:<5} NOTIFY_PID:{:<6}",
+            inner.num_bytes, notify, signo, pid
+        );
+        status
+    }
+
+    fn check_io_events(&self) -> IoEvents {
+        let inner = self.inner.lock();
+
+        let mut events = IoEvents::empty();
+        if inner.num_msgs > 0 {
+            events |= IoEvents::IN;
+        }
+        if inner.num_msgs < self.attr.max_msgs {
+            events |= IoEvents::OUT;
+        }
+
+        events
+    }
+
+    fn touch_mtime(&self) {
+        let now = RealTimeCoarseClock::get().read_time();
+        let mut metadata = self.metadata.write();
+        metadata.atime = now;
+        metadata.mtime = now;
+        metadata.ctime = now;
+    }
+}
+
+impl Drop for MessageQueue {
+    fn drop(&mut self) {
+        uncharge_user(self.charged_uid, self.attr.num_charged_bytes());
+
+        if let Some(fs) = self.fs.upgrade() {
+            fs.num_queues.fetch_sub(1, Ordering::Relaxed);
+        }
+    }
+}
+
+fn charge_user(uid: Uid, bytes: u64, limit: u64) -> Result<()> {
+    let mut user_mq_bytes = USER_MQ_BYTES.lock();
+
+    let charged_bytes = user_mq_bytes.entry(uid.into()).or_insert(0);
+    match charged_bytes.checked_add(bytes) {
+        Some(new_bytes) if new_bytes <= limit => {
+            *charged_bytes = new_bytes;
+            Ok(())
+        }
+        _ => return_errno_with_message!(Errno::EMFILE, "RLIMIT_MSGQUEUE is exceeded"),
+    }
+}
+
+fn uncharge_user(uid: Uid, bytes: u64) {
+    let mut user_mq_bytes = USER_MQ_BYTES.lock();
+
+    let charged_bytes = user_mq_bytes.get_mut(&uid.into()).unwrap();
+    *charged_bytes -= bytes;
+    if *charged_bytes == 0 {
+        user_mq_bytes.remove(&uid.into());
+    }
+}
+
+impl Inode for MessageQueue {
+    fn size(&self) -> usize {
+        FILENT_SIZE
+    }
+
+    fn resize(&self, _new_size: usize) -> Result<()> {
+        Ok(())
+    }
+
+    fn metadata(&self) -> Metadata {
+        *self.metadata.read()
+    }
+
+    fn ino(&self) -> u64 {
+        self.metadata.read().ino
+    }
+
+    fn type_(&self) -> InodeType {
+        InodeType::File
+    }
+
+    fn mode(&self) -> Result<InodeMode> {
+        Ok(self.metadata.read().mode)
+    }
+
+    fn set_mode(&self, mode: InodeMode) -> Result<()> {
+        self.metadata.write().mode = mode;
+        Ok(())
+    }
+
+    fn owner(&self) -> Result<Uid> {
+        Ok(self.metadata.read().uid)
+    }
+
+    fn set_owner(&self, uid: Uid) -> Result<()> {
+        self.metadata.write().uid = uid;
+        Ok(())
+    }
+
+    fn group(&self) -> Result<Gid> {
+        Ok(self.metadata.read().gid)
+    }
+
+    fn set_group(&self, gid: Gid) -> Result<()> {
+        self.metadata.write().gid = gid;
+        Ok(())
+    }
+
+    fn atime(&self) -> Duration {
+        self.metadata.read().atime
+    }
+
+    fn set_atime(&self, time: Duration) {
+        self.metadata.write().atime = time;
+    }
+
+    fn mtime(&self) -> Duration {
+        self.metadata.read().mtime
+    }
+
+    fn set_mtime(&self, time: Duration) {
+        self.metadata.write().mtime = time;
+    }
+
+    fn ctime(&self) -> Duration {
+        self.metadata.read().ctime
+    }
+
+    fn set_ctime(&self, time: Duration) {
+        self.metadata.write().ctime = time;
+    }
+
+    fn read_at(&self, offset: usize, writer: &mut VmWriter) -> Result<usize> {
+        let status = self.status().into_bytes();
+        let start = status.len().min(offset);
+        let end = status.len().min(offset + writer.avail());
+        let len = end - start;
+        writer.write_fallible(&mut (&status[start..end]).into())?;
+        Ok(len)
+    }
+
+    fn write_at(&self, _offset: usize, _reader: &mut VmReader) -> Result<usize> {
+        return_errno_with_message!(Errno::EINVAL, "the message queue cannot be written");
+    }
+
+    fn poll(&self, mask: IoEvents, poller: Option<&mut PollHandle>) -> IoEvents {
+        self.pollee
+            .poll_with(mask, poller, || self.check_io_events())
+    }
+
+    fn is_dentry_cacheable(&self) -> bool {
+        // The queue may be unlinked via other mounts of the filesystem.
+        false
+    }
+
+    fn fs(&self) -> Arc<dyn FileSystem> {
+        self.fs.upgrade().unwrap()
+    }
+}
+
+/// A notification registered by `mq_notify`.
+///
+/// The notification is triggered only once, when a message arrives at the empty queue.
+pub struct Notification {
+    owner: Weak<Process>,
+    owner_pid: Pid,
+    kind: NotifyKind,
+}
+
+/// How a notification is delivered.
+pub enum NotifyKind {
+    /// `SIGEV_NONE`: Nothing is delivered.
+    None,
+    /// `SIGEV_SIGNAL`: A signal is sent to the owner, unless the signal number is zero.
+    Signal {
+        num: Option<SigNum>,
+        value: sigval_t,
+    },
+    /// `SIGEV_THREAD`: The cookie is sent to the netlink socket.
+    ///
+    /// The C library implements `SIGEV_THREAD` by creating a helper thread that waits for the
+    /// cookie on the socket and then creates the notification thread.
+    Thread {
+        socket: Arc<dyn FileLike>,
+        cookie: [u8; NOTIFY_COOKIE_LEN],
+    },
+}
+
+impl NotifyKind {
+    fn sigev_notify(&self) -> i32 {
+        match self {
+            Self::Signal { .. } => 0,
+            Self::None => 1,
+            Self::Thread { .. } => 2,
+        }
+    }
+
+    fn signo(&self) -> u8 {
+        match self {
+            Self::Signal { num: Some(num), .. } => num.as_u8(),
+            _ => 0,
+        }
+    }
+}
+
+/// The last byte of the cookie, which tells the C library what happened.
+#[repr(u8)]
+enum CookieStatus {
+    WokenUp = 1,
+    Removed = 2,
+}
+
+impl Notification {
+    /// Creates a notification owned by the current process.
+    pub fn new(kind: NotifyKind, ctx: &Context) -> Self {
+        Self {
+            owner: Arc::downgrade(&ctx.process),
+            owner_pid: ctx.process.pid(),
+            kind,
+        }
+    }
+
+    fn notify(self, ctx: &Context) {
+        match self.kind {
+            NotifyKind::None | NotifyKind::Signal { num: None, .. } => (),
+            NotifyKind::Signal {
+                num: Some(num),
+                value,
+            } => {
+                let Some(owner) = self.owner.upgrade() else {
+                    return;
+                };
+                let mut info = siginfo_t::new(num, SI_MESGQ);
+                // The sender may be invisible in the PID namespace of the owner.
+                let pid = owner.pid_ns().local_id(ctx.process.pid()).unwrap_or(0);
+                info.set_si_pid_uid(pid, ctx.posix_thread.credentials().ruid());
+                info.set_si_value(value);
+                owner.enqueue_signal(UserInfoSignal::new(num, info));
+            }
+            NotifyKind::Thread { socket, cookie } => {
+                send_cookie(&socket, cookie, CookieStatus::WokenUp);
+            }
+        }
+    }
+
+    fn remove(self) {
+        if let NotifyKind::Thread { socket, cookie } = self.kind {
+            send_cookie(&socket, cookie, CookieStatus::Removed);
+        }
+    }
+}
+
+fn send_cookie(
+    socket: &Arc<dyn FileLike>,
+    mut cookie: [u8; NOTIFY_COOKIE_LEN],
+    status: CookieStatus,
+) {
+    cookie[NOTIFY_COOKIE_LEN - 1] = status as u8;
+
+    let socket = socket.downcast_ref::<NetlinkSocket>().unwrap();
+    socket.deliver(cookie.to_vec());
+}
+
+/// The attributes of a queue, which are used by `mq_open` and `mq_getsetattr`.
+#[repr(C)]
+#[derive(Debug, Default, Clone, Copy, Pod)]
+pub struct mq_attr {
+    /// The flags of the queue descriptor, which can be `O_NONBLOCK`.
+    pub mq_flags: i64,
+    /// The maximum number of messages in the queue.
+    pub mq_maxmsg: i64,
+    /// The maximum size of a message.
+    pub mq_msgsize: i64,
+    /// The number of messages in the queue.
+    pub mq_curmsgs: i64,
+    __reserved: [i64; 4],
+}
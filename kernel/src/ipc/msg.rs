@@ -0,0 +1,374 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! System V message queues.
+
+use alloc::collections::VecDeque;
+use core::sync::atomic::{AtomicBool, Ordering};
+
+use aster_rights::ReadOp;
+use ostd::sync::WaitQueue;
+
+use super::{
+    ids::{IpcIds, IpcObject},
+    ipc64_perm, key_t, IpcPermission,
+};
+use crate::{
+    prelude::*,
+    process::{Credentials, Pid},
+    time::clocks::RealTimeCoarseClock,
+};
+
+// The following constant values are derived from the default values in Linux.
+
+/// Maximum number of message queues.
+pub const MSGMNI: usize = 32000;
+/// Maximum size of a message.
+pub const MSGMAX: usize = 8192;
+/// Default maximum number of bytes in a message queue.
+pub const MSGMNB: usize = 16384;
+
+bitflags! {
+    /// The flags of `msgsnd` and `msgrcv`.
+    pub struct MsgFlags: u32 {
+        /// Return an error instead of waiting.
+        const IPC_NOWAIT = 0o4000;
+        /// Truncate the message if it is too long.
+        const MSG_NOERROR = 0o10000;
+        /// Receive the first message whose type is not the requested one.
+        const MSG_EXCEPT = 0o20000;
+        /// Copy the message instead of removing it.
+        const MSG_COPY = 0o40000;
+    }
+}
+
+/// A System V message queue.
+pub struct MessageQueue {
+    permission: IpcPermission,
+    inner: SpinLock<MessageQueueInner>,
+    /// The queue of the senders and the receivers waiting for the queue to change.
+    wait_queue: WaitQueue,
+    is_removed: AtomicBool,
+}
+
+struct MessageQueueInner {
+    messages: VecDeque<Message>,
+    /// The number of bytes of the messages in the queue.
+    num_bytes: usize,
+    /// The maximum number of bytes in the queue.
+    max_bytes: usize,
+    /// Last send time.
+    stime: u64,
+    /// Last receive time.
+    rtime: u64,
+    /// Creation time or last modification via `msgctl`.
+    ctime: u64,
+    /// PID of the process that last sent a message.
+    last_send_pid: Pid,
+    /// PID of the process that last received a message.
+    last_recv_pid: Pid,
+}
+
+/// A message in a message queue.
+pub struct Message {
+    mtype: i64,
+    text: Box<[u8]>,
+}
+
+impl Message {
+    /// Creates a message with a positive type.
+    pub fn new(mtype: i64, text: Box<[u8]>) -> Result<Self> {
+        if mtype < 1 {
+            return_errno_with_message!(Errno::EINVAL, "the message type must be positive");
+        }
+        if text.len() > MSGMAX {
+            return_errno_with_message!(Errno::EINVAL, "the message is too long");
+        }
+        Ok(Self { mtype, text })
+    }
+
+    pub fn mtype(&self) -> i64 {
+        self.mtype
+    }
+
+    pub fn text(&self) -> &[u8] {
+        &self.text
+    }
+}
+
+impl MessageQueue {
+    fn new(key: key_t, mode: u16, credentials: &Credentials<ReadOp>) -> Self {
+        let permission = IpcPermission::new(key, credentials.euid(), credentials.egid(), mode);
+
+        Self {
+            permission,
+            inner: SpinLock::new(MessageQueueInner {
+                messages: VecDeque::new(),
+                num_bytes: 0,
+                max_bytes: MSGMNB,
+                stime: 0,
+                rtime: 0,
+                ctime: now(),
+                last_send_pid: 0,
+                last_recv_pid: 0,
+            }),
+            wait_queue: WaitQueue::new(),
+            is_removed: AtomicBool::new(false),
+        }
+    }
+
+    fn try_send(
+        &self,
+        message: &mut Option<Message>,
+        flags: MsgFlags,
+        pid: Pid,
+    ) -> Option<Result<()>> {
+        if self.is_removed.load(Ordering::Relaxed) {
+            return Some(Err(Error::with_message(
+                Errno::EIDRM,
+                "the message queue is removed",
+            )));
+        }
+
+        let mut inner = self.inner.lock();
+        let len = message.as_ref().unwrap().text.len();
+        // Like Linux, the number of messages is also limited by the maximum number of bytes,
+        // so that a queue cannot be filled with infinite empty messages.
+        if inner.num_bytes + len > inner.max_bytes || inner.messages.len() + 1 > inner.max_bytes {
+            if flags.contains(MsgFlags::IPC_NOWAIT) {
+                return Some(Err(Error::with_message(
+                    Errno::EAGAIN,
+                    "the message queue is full",
+                )));
+            }
+            return None;
+        }
+
+        inner.messages.push_back(message.take().unwrap());
+        inner.num_bytes += len;
+        inner.stime = now();
+        inner.last_send_pid = pid;
+        Some(Ok(()))
+    }
+
+    fn try_receive(
+        &self,
+        max_len: usize,
+        msgtyp: i64,
+        flags: MsgFlags,
+        pid: Pid,
+    ) -> Option<Result<Message>> {
+        if self.is_removed.load(Ordering::Relaxed) {
+            return Some(Err(Error::with_message(
+                Errno::EIDRM,
+                "the message queue is removed",
+            )));
+        }
+
+        let mut inner = self.inner.lock();
+        let Some(index) = find_message(&inner.messages, msgtyp, flags) else {
+            if flags.contains(MsgFlags::IPC_NOWAIT) {
+                return Some(Err(Error::with_message(
+                    Errno::ENOMSG,
+                    "no message of the type is in the queue",
+                )));
+            }
+            return None;
+        };
+
+        if inner.messages[index].text.len() > max_len && !flags.contains(MsgFlags::MSG_NOERROR) {
+            return Some(Err(Error::with_message(
+                Errno::E2BIG,
+                "the message is too long",
+            )));
+        }
+
+        let message = inner.messages.remove(index).unwrap();
+        inner.num_bytes -= message.text.len();
+        inner.rtime = now();
+        inner.last_recv_pid = pid;
+        Some(Ok(message))
+    }
+
+    fn stat(&self, ctx: &Context) -> msqid64_ds {
+        let inner = self.inner.lock();
+        let pid_ns = ctx.process.pid_ns();
+
+        msqid64_ds {
+            msg_perm: self.permission.to_c(),
+            msg_stime: inner.stime as i64,
+            msg_rtime: inner.rtime as i64,
+            msg_ctime: inner.ctime as i64,
+            msg_cbytes: inner.num_bytes as u64,
+            msg_qnum: inner.messages.len() as u64,
+            msg_qbytes: inner.max_bytes as u64,
+            // The processes may be invisible in the PID namespace of the current process.
+            msg_lspid: pid_ns.local_id(inner.last_send_pid).unwrap_or(0) as i32,
+            msg_lrpid: pid_ns.local_id(inner.last_recv_pid).unwrap_or(0) as i32,
+            ..Default::default()
+        }
+    }
+}
+
+impl IpcObject for MessageQueue {
+    fn permission(&self) -> &IpcPermission {
+        &self.permission
+    }
+}
+
+/// Finds the index of the first message that matches `msgtyp`.
+///
+/// If `msgtyp` is zero, the first message is matched. If `msgtyp` is positive, the first
+/// message of the type is matched, or the first message of other types if `MSG_EXCEPT` is
+/// specified. If `msgtyp` is negative, the first message with the lowest type that is less
+/// than or equal to the absolute value of `msgtyp` is matched.
+fn find_message(messages: &VecDeque<Message>, msgtyp: i64, flags: MsgFlags) -> Option<usize> {
+    let mut iter = messages.iter().enumerate();
+
+    if msgtyp == 0 {
+        return iter.next().map(|(index, _)| index);
+    }
+
+    if msgtyp > 0 {
+        let is_except = flags.contains(MsgFlags::MSG_EXCEPT);
+        return iter
+            .find(|(_, message)| (message.mtype == msgtyp) != is_except)
+            .map(|(index, _)| index);
+    }
+
+    let max_type = msgtyp.unsigned_abs();
+    iter.filter(|(_, message)| message.mtype.unsigned_abs() <= max_type)
+        .min_by_key(|(_, message)| message.mtype)
+        .map(|(index, _)| index)
+}
+
+/// The message queues in an IPC namespace.
+pub struct MessageQueues {
+    queues: IpcIds<MessageQueue>,
+}
+
+impl MessageQueues {
+    pub(super) fn new() -> Self {
+        Self {
+            queues: IpcIds::new(MSGMNI),
+        }
+    }
+
+    /// Gets the ID of the queue with the key, or creates a new queue if necessary.
+    pub fn get_or_create(&self, key: key_t, flags: i32, ctx: &Context) -> Result<key_t> {
+        let credentials = ctx.posix_thread.credentials();
+        self.queues.get_or_create(
+            key,
+            flags,
+            &credentials,
+            |_| Ok(()),
+            |_| {
+                let mode = (flags & 0o777) as u16;
+                Ok(MessageQueue::new(key, mode, &credentials))
+            },
+        )
+    }
+
+    /// Sends the message to the queue.
+    ///
+    /// If the queue is full, this method waits until there is enough space, unless
+    /// `IPC_NOWAIT` is specified.
+    pub fn send(&self, id: key_t, message: Message, flags: MsgFlags, ctx: &Context) -> Result<()> {
+        let queue = self.queues.get(id)?;
+        queue
+            .permission
+            .check(&ctx.posix_thread.credentials(), 0o2)?;
+
+        let pid = ctx.process.pid();
+        let mut message = Some(message);
+        queue
+            .wait_queue
+            .pause_until(|| queue.try_send(&mut message, flags, pid))??;
+
+        queue.wait_queue.wake_all();
+        Ok(())
+    }
+
+    /// Receives a message of at most `max_len` bytes from the queue.
+    ///
+    /// If there are no matching messages, this method waits until one is sent, unless
+    /// `IPC_NOWAIT` is specified. If `MSG_NOERROR` is specified, a longer message is truncated.
+    pub fn receive(
+        &self,
+        id: key_t,
+        max_len: usize,
+        msgtyp: i64,
+        flags: MsgFlags,
+        ctx: &Context,
+    ) -> Result<Message> {
+        if flags.contains(MsgFlags::MSG_COPY) {
+            return_errno_with_message!(Errno::ENOSYS, "MSG_COPY is not supported");
+        }
+
+        let queue = self.queues.get(id)?;
+        queue
+            .permission
+            .check(&ctx.posix_thread.credentials(), 0o4)?;
+
+        let pid = ctx.process.pid();
+        let mut message = queue
+            .wait_queue
+            .pause_until(|| queue.try_receive(max_len, msgtyp, flags, pid))??;
+
+        queue.wait_queue.wake_all();
+
+        if message.text.len() > max_len {
+            message.text = message.text[..max_len].into();
+        }
+        Ok(message)
+    }
+
+    /// Returns the status of the queue for `IPC_STAT`.
+    pub fn stat(&self, id: key_t, ctx: &Context) -> Result<msqid64_ds> {
+        let queue = self.queues.get(id)?;
+        queue
+            .permission
+            .check(&ctx.posix_thread.credentials(), 0o4)?;
+
+        Ok(queue.stat(ctx))
+    }
+
+    /// Removes the queue for `IPC_RMID`.
+    ///
+    /// The waiting senders and receivers fail with `EIDRM`.
+    pub fn remove(&self, id: key_t, ctx: &Context) -> Result<()> {
+        let queue = self.queues.get(id)?;
+        if !queue
+            .permission
+            .is_owned_by(&ctx.posix_thread.credentials())
+        {
+            return_errno_with_message!(Errno::EPERM, "the queue is not owned by the caller");
+        }
+
+        self.queues.remove(id);
+        queue.is_removed.store(true, Ordering::Relaxed);
+        queue.wait_queue.wake_all();
+
+        Ok(())
+    }
+}
+
+fn now() -> u64 {
+    RealTimeCoarseClock::get().read_time().as_secs()
+}
+
+/// The status of a message queue, which is reported by `IPC_STAT`.
+#[repr(C)]
+#[derive(Debug, Default, Clone, Copy, Pod)]
+pub struct msqid64_ds {
+    msg_perm: ipc64_perm,
+    msg_stime: i64,
+    msg_rtime: i64,
+    msg_ctime: i64,
+    msg_cbytes: u64,
+    msg_qnum: u64,
+    msg_qbytes: u64,
+    msg_lspid: i32,
+    msg_lrpid: i32,
+    __unused4: u64,
+    __unused5: u64,
+}
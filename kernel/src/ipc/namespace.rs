@@ -0,0 +1,59 @@
+// SPDX-License-Identifier: MPL-2.0
+
+use spin::Once;
+
+use super::{
+    mqueue::PosixMessageQueues, msg::MessageQueues, semaphore::system_v::sem_set::SemaphoreSets,
+    shm::SharedMemorySegments,
+};
+use crate::prelude::*;
+
+/// An IPC namespace.
+///
+/// An IPC namespace isolates the System V IPC objects and the POSIX message queues. The objects
+/// created in one namespace are invisible to the other namespaces.
+pub struct IpcNamespace {
+    sem_sets: SemaphoreSets,
+    shm_segments: SharedMemorySegments,
+    msg_queues: MessageQueues,
+    posix_msg_queues: PosixMessageQueues,
+}
+
+impl IpcNamespace {
+    /// Gets the initial IPC namespace.
+    pub fn get_init_singleton() -> &'static Arc<IpcNamespace> {
+        static INIT: Once<Arc<IpcNamespace>> = Once::new();
+
+        INIT.call_once(Self::new)
+    }
+
+    /// Creates a new IPC namespace without any IPC objects.
+    pub fn new() -> Arc<Self> {
+        Arc::new(Self {
+            sem_sets: SemaphoreSets::new(),
+            shm_segments: SharedMemorySegments::new(),
+            msg_queues: MessageQueues::new(),
+            posix_msg_queues: PosixMessageQueues::new(),
+        })
+    }
+
+    /// Returns the System V semaphore sets in the namespace.
+    pub fn sem_sets(&self) -> &SemaphoreSets {
+        &self.sem_sets
+    }
+
+    /// Returns the System V shared memory segments in the namespace.
+    pub fn shm_segments(&self) -> &SharedMemorySegments {
+        &self.shm_segments
+    }
+
+    /// Returns the System V message queues in the namespace.
+    pub fn msg_queues(&self) -> &MessageQueues {
+        &self.msg_queues
+    }
+
+    /// Returns the POSIX message queues in the namespace.
+    pub fn posix_msg_queues(&self) -> &PosixMessageQueues {
+        &self.posix_msg_queues
+    }
+}
@@ -1,5 +1,6 @@
 // SPDX-License-Identifier: MPL-2.0
 
+use alloc::collections::btree_map::BTreeMap;
 use core::{
     slice::Iter,
     sync::atomic::{AtomicU16, Ordering},
@@ -9,9 +10,9 @@ use core::{
 use atomic_integer_wrapper::define_atomic_version_of_integer_like_type;
 use ostd::sync::{PreemptDisabled, Waiter, Waker};
 
-use super::sem_set::{SemSetInner, SEMVMX};
+use super::sem_set::{SemSetInner, SEMAEM, SEMVMX};
 use crate::{
-    ipc::{key_t, semaphore::system_v::sem_set::sem_sets, IpcFlags},
+    ipc::{key_t, IpcFlags},
     prelude::*,
     process::Pid,
     time::{clocks::JIFFIES_TIMER_MANAGER, timer::Timeout},
@@ -101,6 +102,8 @@ pub struct Semaphore {
     /// - through semctl with SETVAL and SETALL
     /// - through SEM_UNDO when task exit
     latest_modified_pid: Pid,
+    /// The adjustments to undo the operations with `SEM_UNDO` when the processes exit.
+    adjustments: BTreeMap<Pid, i32>,
 }
 
 impl Semaphore {
@@ -120,10 +123,26 @@ impl Semaphore {
         self.latest_modified_pid
     }
 
+    /// Takes the adjustment of the process, which should be added to the value when the
+    /// process exits.
+    pub(super) fn take_adjustment(&mut self, pid: Pid) -> Option<i32> {
+        self.adjustments.remove(&pid)
+    }
+
+    /// Clears the adjustments of all processes since the value is set explicitly.
+    pub(super) fn clear_adjustments(&mut self) {
+        self.adjustments.clear();
+    }
+
+    fn adjustment(&self, pid: Pid) -> i32 {
+        self.adjustments.get(&pid).copied().unwrap_or(0)
+    }
+
     pub(super) fn new(val: i32) -> Self {
         Self {
             val,
             latest_modified_pid: current!().pid(),
+            adjustments: BTreeMap::new(),
         }
     }
 }
@@ -153,7 +172,8 @@ pub fn sem_op(
         warn!("Found duplicate sop");
     }
 
-    let local_sem_sets = sem_sets();
+    let ipc_ns = ctx.posix_thread.ns_proxy().lock().ipc_ns().clone();
+    let local_sem_sets = ipc_ns.sem_sets().sets();
     let sem_set = local_sem_sets
         .get(&sem_id)
         .ok_or(Error::new(Errno::EINVAL))?;
@@ -205,7 +225,7 @@ pub fn sem_op(
         Status::Removed => Err(Error::new(Errno::EIDRM)),
         Status::Pending => {
             // FIXME: Getting sem_sets maybe time-consuming.
-            let sem_sets = sem_sets();
+            let sem_sets = ipc_ns.sem_sets().sets();
             let sem_set = sem_sets.get(&sem_id).ok_or(Error::new(Errno::EINVAL))?;
             let mut inner = sem_set.inner();
 
@@ -345,16 +365,31 @@ fn perform_atomic_semop(sems: &mut Box<[Semaphore]>, pending_op: &mut PendingOp)
             return_errno!(Errno::ERANGE);
         }
         if flags.contains(IpcFlags::SEM_UNDO) {
-            todo!()
+            let adjustment = sem.adjustment(pending_op.pid) - i32::from(op.sem_op);
+            if !(-SEMAEM - 1..=SEMAEM).contains(&adjustment) {
+                return_errno!(Errno::ERANGE);
+            }
         }
     }
 
     // Success, do operation
     for op in pending_op.sops_iter() {
         let sem = &mut sems[op.sem_num as usize];
-        if op.sem_op != 0 {
-            sem.val += i32::from(op.sem_op);
-            sem.latest_modified_pid = pending_op.pid;
+        if op.sem_op == 0 {
+            continue;
+        }
+
+        sem.val += i32::from(op.sem_op);
+        sem.latest_modified_pid = pending_op.pid;
+
+        let flags = IpcFlags::from_bits_truncate(op.sem_flags as u32);
+        if flags.contains(IpcFlags::SEM_UNDO) {
+            let adjustment = sem.adjustment(pending_op.pid) - i32::from(op.sem_op);
+            if adjustment == 0 {
+                sem.adjustments.remove(&pending_op.pid);
+            } else {
+                sem.adjustments.insert(pending_op.pid, adjustment);
+            }
         }
     }
 
@@ -9,7 +9,6 @@ use core::{
 use aster_rights::ReadOp;
 use id_alloc::IdAlloc;
 use ostd::sync::{PreemptDisabled, RwLockReadGuard, RwLockWriteGuard};
-use spin::Once;
 
 use super::{
     sem::{update_pending_alter, wake_const_ops, PendingOp, Status},
@@ -49,6 +48,8 @@ pub struct SemaphoreSet {
     sem_ctime: AtomicU64,
     /// Last semop time.
     sem_otime: AtomicU64,
+    /// The allocator of the semaphore set IDs, which frees the ID when the set is dropped.
+    id_allocator: Arc<SpinLock<IdAlloc>>,
 }
 
 #[derive(Debug)]
@@ -121,6 +122,7 @@ impl SemaphoreSet {
 
         sem.set_val(val);
         sem.set_latest_modified_pid(pid);
+        sem.clear_adjustments();
 
         let mut wake_queue = LinkedList::new();
         if val == 0 {
@@ -128,18 +130,37 @@ impl SemaphoreSet {
         } else {
             update_pending_alter(sems, pending_alter, pending_const, &mut wake_queue);
         }
-
-        for wake_op in wake_queue {
-            wake_op.set_status(Status::Normal);
-            if let Some(waker) = wake_op.waker() {
-                waker.wake_up();
-            }
-        }
+        wake_up_ops(wake_queue);
 
         self.update_ctime();
         Ok(())
     }
 
+    /// Undoes the operations with `SEM_UNDO` of the exiting process.
+    fn undo_adjustments(&self, pid: Pid) {
+        let mut inner = self.inner();
+        let (sems, pending_alter, pending_const) = inner.field_mut();
+
+        let mut is_changed = false;
+        for sem in sems.iter_mut() {
+            let Some(adjustment) = sem.take_adjustment(pid) else {
+                continue;
+            };
+            // Like Linux, the value is clamped instead of blocking the exiting process.
+            sem.set_val((sem.val() + adjustment).clamp(0, SEMVMX));
+            sem.set_latest_modified_pid(pid);
+            is_changed = true;
+        }
+        if !is_changed {
+            return;
+        }
+
+        let mut wake_queue = LinkedList::new();
+        wake_const_ops(sems, pending_const, &mut wake_queue);
+        update_pending_alter(sems, pending_alter, pending_const, &mut wake_queue);
+        wake_up_ops(wake_queue);
+    }
+
     pub fn get<T>(&self, sem_num: usize, func: &dyn Fn(&Semaphore) -> T) -> Result<T> {
         let inner = self.inner();
         Ok(func(
@@ -173,7 +194,13 @@ impl SemaphoreSet {
         self.inner.lock()
     }
 
-    fn new(key: key_t, nsems: usize, mode: u16, credentials: Credentials<ReadOp>) -> Result<Self> {
+    fn new(
+        key: key_t,
+        nsems: usize,
+        mode: u16,
+        credentials: Credentials<ReadOp>,
+        id_allocator: Arc<SpinLock<IdAlloc>>,
+    ) -> Result<Self> {
         debug_assert!(nsems <= SEMMSL);
 
         let mut sems = Vec::with_capacity(nsems);
@@ -181,8 +208,7 @@ impl SemaphoreSet {
             sems.push(Semaphore::new(0));
         }
 
-        let permission =
-            IpcPermission::new_sem_perm(key, credentials.euid(), credentials.egid(), mode);
+        let permission = IpcPermission::new(key, credentials.euid(), credentials.egid(), mode);
 
         Ok(Self {
             nsems,
@@ -194,10 +220,20 @@ impl SemaphoreSet {
                 pending_alter: LinkedList::new(),
                 pending_const: LinkedList::new(),
             }),
+            id_allocator,
         })
     }
 }
 
+fn wake_up_ops(wake_queue: LinkedList<PendingOp>) {
+    for wake_op in wake_queue {
+        wake_op.set_status(Status::Normal);
+        if let Some(waker) = wake_op.waker() {
+            waker.wake_up();
+        }
+    }
+}
+
 impl Drop for SemaphoreSet {
     fn drop(&mut self) {
         let mut inner = self.inner();
@@ -219,96 +255,113 @@ impl Drop for SemaphoreSet {
         }
         pending_const.clear();
 
-        ID_ALLOCATOR
-            .get()
-            .unwrap()
+        self.id_allocator
             .lock()
             .free(self.permission.key() as usize);
     }
 }
 
-pub fn create_sem_set_with_id(
-    id: key_t,
-    nsems: usize,
-    mode: u16,
-    credentials: Credentials<ReadOp>,
-) -> Result<()> {
-    debug_assert!(nsems <= SEMMSL);
-    debug_assert!(id > 0);
-    if id as usize > SEMMNI {
-        return_errno_with_message!(Errno::ENOENT, "id larger than SEMMNI");
-    }
-
-    ID_ALLOCATOR
-        .get()
-        .unwrap()
-        .lock()
-        .alloc_specific(id as usize)
-        .ok_or(Error::new(Errno::EEXIST))?;
-
-    let mut sem_sets = SEMAPHORE_SETS.write();
-    sem_sets.insert(id, SemaphoreSet::new(id, nsems, mode, credentials)?);
-
-    Ok(())
+/// The semaphore sets in an IPC namespace.
+pub struct SemaphoreSets {
+    id_allocator: Arc<SpinLock<IdAlloc>>,
+    sets: RwLock<BTreeMap<key_t, SemaphoreSet>>,
 }
 
-/// Checks the semaphore. Return Ok if the semaphore exists and pass the check.
-pub fn check_sem(id: key_t, nsems: Option<usize>, required_perm: PermissionMode) -> Result<()> {
-    debug_assert!(id > 0);
+impl SemaphoreSets {
+    pub(in crate::ipc) fn new() -> Self {
+        let mut id_alloc = IdAlloc::with_capacity(SEMMNI + 1);
+        // Remove the first index 0
+        id_alloc.alloc();
 
-    let sem_sets = SEMAPHORE_SETS.read();
-    let sem_set = sem_sets.get(&id).ok_or(Error::new(Errno::ENOENT))?;
+        Self {
+            id_allocator: Arc::new(SpinLock::new(id_alloc)),
+            sets: RwLock::new(BTreeMap::new()),
+        }
+    }
 
-    if let Some(nsems) = nsems {
+    pub fn create_with_id(
+        &self,
+        id: key_t,
+        nsems: usize,
+        mode: u16,
+        credentials: Credentials<ReadOp>,
+    ) -> Result<()> {
         debug_assert!(nsems <= SEMMSL);
-        if nsems > sem_set.nsems() {
-            return_errno!(Errno::EINVAL);
+        debug_assert!(id > 0);
+        if id as usize > SEMMNI {
+            return_errno_with_message!(Errno::ENOENT, "id larger than SEMMNI");
         }
-    }
 
-    if !required_perm.is_empty() {
-        // TODO: Support permission check
-        warn!("Semaphore doesn't support permission check now");
-    }
+        self.id_allocator
+            .lock()
+            .alloc_specific(id as usize)
+            .ok_or(Error::new(Errno::EEXIST))?;
 
-    Ok(())
-}
+        let sem_set = SemaphoreSet::new(id, nsems, mode, credentials, self.id_allocator.clone())?;
+        self.sets.write().insert(id, sem_set);
 
-pub fn create_sem_set(nsems: usize, mode: u16, credentials: Credentials<ReadOp>) -> Result<key_t> {
-    debug_assert!(nsems <= SEMMSL);
+        Ok(())
+    }
 
-    let id = ID_ALLOCATOR
-        .get()
-        .unwrap()
-        .lock()
-        .alloc()
-        .ok_or(Error::new(Errno::ENOSPC))? as i32;
+    /// Checks the semaphore. Return Ok if the semaphore exists and pass the check.
+    pub fn check(
+        &self,
+        id: key_t,
+        nsems: Option<usize>,
+        required_perm: PermissionMode,
+    ) -> Result<()> {
+        debug_assert!(id > 0);
+
+        let sem_sets = self.sets.read();
+        let sem_set = sem_sets.get(&id).ok_or(Error::new(Errno::ENOENT))?;
+
+        if let Some(nsems) = nsems {
+            debug_assert!(nsems <= SEMMSL);
+            if nsems > sem_set.nsems() {
+                return_errno!(Errno::EINVAL);
+            }
+        }
 
-    let mut sem_sets = SEMAPHORE_SETS.write();
-    sem_sets.insert(id, SemaphoreSet::new(id, nsems, mode, credentials)?);
+        if !required_perm.is_empty() {
+            // TODO: Support permission check
+            warn!("Semaphore doesn't support permission check now");
+        }
 
-    Ok(id)
-}
+        Ok(())
+    }
 
-pub fn sem_sets<'a>() -> RwLockReadGuard<'a, BTreeMap<key_t, SemaphoreSet>, PreemptDisabled> {
-    SEMAPHORE_SETS.read()
-}
+    pub fn create(
+        &self,
+        nsems: usize,
+        mode: u16,
+        credentials: Credentials<ReadOp>,
+    ) -> Result<key_t> {
+        debug_assert!(nsems <= SEMMSL);
 
-pub fn sem_sets_mut<'a>() -> RwLockWriteGuard<'a, BTreeMap<key_t, SemaphoreSet>, PreemptDisabled> {
-    SEMAPHORE_SETS.write()
-}
+        let id = self
+            .id_allocator
+            .lock()
+            .alloc()
+            .ok_or(Error::new(Errno::ENOSPC))? as i32;
 
-static ID_ALLOCATOR: Once<SpinLock<IdAlloc>> = Once::new();
+        let sem_set = SemaphoreSet::new(id, nsems, mode, credentials, self.id_allocator.clone())?;
+        self.sets.write().insert(id, sem_set);
 
-/// Semaphore sets in system
-static SEMAPHORE_SETS: RwLock<BTreeMap<key_t, SemaphoreSet>> = RwLock::new(BTreeMap::new());
+        Ok(id)
+    }
 
-pub(super) fn init() {
-    ID_ALLOCATOR.call_once(|| {
-        let mut id_alloc = IdAlloc::with_capacity(SEMMNI + 1);
-        // Remove the first index 0
-        id_alloc.alloc();
+    /// Undoes the operations with `SEM_UNDO` of the exiting process in all semaphore sets.
+    pub fn undo_adjustments(&self, pid: Pid) {
+        for sem_set in self.sets.read().values() {
+            sem_set.undo_adjustments(pid);
+        }
+    }
 
-        SpinLock::new(id_alloc)
-    });
+    pub fn sets(&self) -> RwLockReadGuard<BTreeMap<key_t, SemaphoreSet>, PreemptDisabled> {
+        self.sets.read()
+    }
+
+    pub fn sets_mut(&self) -> RwLockWriteGuard<BTreeMap<key_t, SemaphoreSet>, PreemptDisabled> {
+        self.sets.write()
+    }
 }
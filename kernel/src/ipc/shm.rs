@@ -0,0 +1,346 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! System V shared memory.
+//!
+//! A shared memory segment is backed by an anonymous VMO, which is mapped as a shared mapping
+//! when the segment is attached. The number of attaches is the number of the mappings of the
+//! VMO, so the attaches inherited by `fork` or removed by `munmap` and `exit` are counted
+//! correctly.
+
+use core::sync::atomic::{AtomicBool, Ordering};
+
+use align_ext::AlignExt;
+use aster_rights::{ReadOp, Rights};
+use ostd::mm::sync_icache_all;
+
+use super::{
+    ids::{IpcIds, IpcObject},
+    ipc64_perm, key_t, IpcPermission,
+};
+use crate::{
+    prelude::*,
+    process::{Credentials, Pid},
+    time::clocks::RealTimeCoarseClock,
+    vm::{
+        perms::VmPerms,
+        vmo::{Vmo, VmoOptions},
+        wx,
+    },
+};
+
+// The following constant values are derived from the default values in Linux.
+
+/// Maximum number of shared memory segments.
+pub const SHMMNI: usize = 4096;
+/// Minimum size of a shared memory segment.
+pub const SHMMIN: usize = 1;
+/// Maximum size of a shared memory segment.
+pub const SHMMAX: usize = usize::MAX - (1 << 24);
+/// The alignment of the attach addresses.
+pub const SHMLBA: usize = PAGE_SIZE;
+
+/// The mode bit reported by `IPC_STAT` if the segment is removed but still attached.
+const SHM_DEST: u32 = 0o1000;
+
+bitflags! {
+    /// The flags of `shmat`.
+    pub struct ShmAtFlags: u32 {
+        /// Attach the segment read-only.
+        const SHM_RDONLY = 0o10000;
+        /// Round the attach address down to the multiple of `SHMLBA`.
+        const SHM_RND = 0o20000;
+        /// Replace the existing mappings in the range.
+        const SHM_REMAP = 0o40000;
+        /// Attach the segment executable.
+        const SHM_EXEC = 0o100000;
+    }
+}
+
+/// A System V shared memory segment.
+pub struct SharedMemory {
+    permission: IpcPermission,
+    /// The size requested by `shmget`.
+    size: usize,
+    vmo: Vmo<Rights>,
+    creator_pid: Pid,
+    inner: Mutex<SharedMemoryInner>,
+    is_removed: AtomicBool,
+}
+
+struct SharedMemoryInner {
+    /// Last attach time.
+    atime: u64,
+    /// Last detach time.
+    dtime: u64,
+    /// Creation time or last modification via `shmctl`.
+    ctime: u64,
+    /// PID of the process that last attached or detached the segment.
+    last_pid: Pid,
+}
+
+impl SharedMemory {
+    fn new(
+        key: key_t,
+        size: usize,
+        mode: u16,
+        credentials: &Credentials<ReadOp>,
+        pid: Pid,
+    ) -> Result<Self> {
+        let vmo = VmoOptions::<Rights>::new(size.align_up(PAGE_SIZE)).alloc()?;
+        let permission = IpcPermission::new(key, credentials.euid(), credentials.egid(), mode);
+
+        Ok(Self {
+            permission,
+            size,
+            vmo,
+            creator_pid: pid,
+            inner: Mutex::new(SharedMemoryInner {
+                atime: 0,
+                dtime: 0,
+                ctime: now(),
+                last_pid: 0,
+            }),
+            is_removed: AtomicBool::new(false),
+        })
+    }
+
+    /// Returns the number of the current attaches.
+    fn num_attaches(&self) -> usize {
+        // The segment holds one capability of the VMO, and each mapping holds another.
+        self.vmo.num_capabilities() - 1
+    }
+
+    fn stat(&self, ctx: &Context) -> shmid64_ds {
+        let inner = self.inner.lock();
+        let pid_ns = ctx.process.pid_ns();
+
+        let mut shm_perm = self.permission.to_c();
+        if self.is_removed() {
+            shm_perm.mode |= SHM_DEST;
+        }
+
+        shmid64_ds {
+            shm_perm,
+            shm_segsz: self.size,
+            shm_atime: inner.atime as i64,
+            shm_dtime: inner.dtime as i64,
+            shm_ctime: inner.ctime as i64,
+            // The processes may be invisible in the PID namespace of the current process.
+            shm_cpid: pid_ns.local_id(self.creator_pid).unwrap_or(0) as i32,
+            shm_lpid: pid_ns.local_id(inner.last_pid).unwrap_or(0) as i32,
+            shm_nattch: self.num_attaches() as u64,
+            ..Default::default()
+        }
+    }
+}
+
+impl IpcObject for SharedMemory {
+    fn permission(&self) -> &IpcPermission {
+        &self.permission
+    }
+
+    fn is_removed(&self) -> bool {
+        self.is_removed.load(Ordering::Relaxed)
+    }
+}
+
+/// The shared memory segments in an IPC namespace.
+pub struct SharedMemorySegments {
+    segments: IpcIds<SharedMemory>,
+}
+
+impl SharedMemorySegments {
+    pub(super) fn new() -> Self {
+        Self {
+            segments: IpcIds::new(SHMMNI),
+        }
+    }
+
+    /// Gets the ID of the segment with the key, or creates a new segment if necessary.
+    pub fn get_or_create(
+        &self,
+        key: key_t,
+        size: usize,
+        flags: i32,
+        ctx: &Context,
+    ) -> Result<key_t> {
+        self.remove_destroyed();
+
+        let credentials = ctx.posix_thread.credentials();
+        self.segments.get_or_create(
+            key,
+            flags,
+            &credentials,
+            |segment| {
+                if segment.size < size {
+                    return_errno_with_message!(Errno::EINVAL, "the segment is too small");
+                }
+                Ok(())
+            },
+            |_| {
+                if !(SHMMIN..=SHMMAX).contains(&size) {
+                    return_errno_with_message!(Errno::EINVAL, "the size is invalid");
+                }
+                let mode = (flags & 0o777) as u16;
+                SharedMemory::new(key, size, mode, &credentials, ctx.process.pid())
+            },
+        )
+    }
+
+    /// Attaches the segment to the address space of the current process.
+    ///
+    /// On success, the address of the attached segment is returned.
+    pub fn attach(
+        &self,
+        id: key_t,
+        addr: Vaddr,
+        flags: ShmAtFlags,
+        ctx: &Context,
+    ) -> Result<Vaddr> {
+        let segment = self.segments.get(id)?;
+
+        let (mut perms, mut requested) = if flags.contains(ShmAtFlags::SHM_RDONLY) {
+            (VmPerms::READ, 0o4)
+        } else {
+            (VmPerms::READ | VmPerms::WRITE, 0o6)
+        };
+        if flags.contains(ShmAtFlags::SHM_EXEC) {
+            perms |= VmPerms::EXEC;
+            requested |= 0o1;
+        }
+        segment
+            .permission
+            .check(&ctx.posix_thread.credentials(), requested)?;
+
+        let addr = if addr % SHMLBA == 0 {
+            addr
+        } else if flags.contains(ShmAtFlags::SHM_RND) {
+            addr.align_down(SHMLBA)
+        } else {
+            return_errno_with_message!(Errno::EINVAL, "the address is not aligned");
+        };
+        let is_remap = flags.contains(ShmAtFlags::SHM_REMAP);
+        if is_remap && addr == 0 {
+            return_errno_with_message!(Errno::EINVAL, "SHM_REMAP requires an address");
+        }
+
+        let len = segment.size.align_up(PAGE_SIZE);
+        wx::check_wx("shmat", &(addr..addr + len), perms, ctx)?;
+        let replaced_range = is_remap.then(|| addr..addr + len);
+        ctx.process.check_address_space_limit(len, replaced_range)?;
+
+        let root_vmar = ctx.thread_local.root_vmar();
+        let mut options = root_vmar
+            .new_map(len, perms)?
+            .vmo(segment.vmo.dup()?)
+            .is_shared(true);
+        if addr != 0 {
+            options = options.offset(addr).can_overwrite(is_remap);
+        }
+        let addr = options.build().map_err(|err| {
+            if err.error() == Errno::EEXIST {
+                Error::with_message(Errno::EINVAL, "the range is occupied")
+            } else {
+                err
+            }
+        })?;
+        if perms.contains(VmPerms::EXEC) {
+            wx::audit_execmem("shmat", &(addr..addr + len), ctx);
+            sync_icache_all();
+        }
+
+        let mut inner = segment.inner.lock();
+        inner.atime = now();
+        inner.last_pid = ctx.process.pid();
+
+        Ok(addr)
+    }
+
+    /// Detaches the segment attached at the address from the current process.
+    pub fn detach(&self, addr: Vaddr, ctx: &Context) -> Result<()> {
+        if addr % PAGE_SIZE != 0 {
+            return_errno_with_message!(Errno::EINVAL, "the address is not aligned");
+        }
+
+        let root_vmar = ctx.thread_local.root_vmar();
+        let segment = match root_vmar.vmo_at(addr) {
+            Some((vmo, 0)) => self.segments.find(|segment| segment.vmo.is_same(&vmo)),
+            _ => None,
+        };
+        let Some(segment) = segment else {
+            return_errno_with_message!(Errno::EINVAL, "no segment is attached at the address");
+        };
+
+        let len = segment.size.align_up(PAGE_SIZE);
+        root_vmar.remove_mapping(addr..addr + len)?;
+
+        let mut inner = segment.inner.lock();
+        inner.dtime = now();
+        inner.last_pid = ctx.process.pid();
+        drop(inner);
+
+        drop(segment);
+        self.remove_destroyed();
+
+        Ok(())
+    }
+
+    /// Returns the status of the segment for `IPC_STAT`.
+    pub fn stat(&self, id: key_t, ctx: &Context) -> Result<shmid64_ds> {
+        let segment = self.segments.get(id)?;
+        segment
+            .permission
+            .check(&ctx.posix_thread.credentials(), 0o4)?;
+
+        Ok(segment.stat(ctx))
+    }
+
+    /// Marks the segment as removed for `IPC_RMID`.
+    ///
+    /// The segment is destroyed after it is detached by all processes.
+    pub fn remove(&self, id: key_t, ctx: &Context) -> Result<()> {
+        let segment = self.segments.get(id)?;
+        if !segment
+            .permission
+            .is_owned_by(&ctx.posix_thread.credentials())
+        {
+            return_errno_with_message!(Errno::EPERM, "the segment is not owned by the caller");
+        }
+
+        segment.is_removed.store(true, Ordering::Relaxed);
+        segment.inner.lock().ctime = now();
+        drop(segment);
+        self.remove_destroyed();
+
+        Ok(())
+    }
+
+    /// Destroys the removed segments that are no longer attached.
+    ///
+    /// The attaches may be removed implicitly by `munmap` or `exit`, so the segments are
+    /// destroyed lazily when the segments are used.
+    fn remove_destroyed(&self) {
+        self.segments
+            .remove_if(|segment| segment.is_removed() && segment.num_attaches() == 0);
+    }
+}
+
+fn now() -> u64 {
+    RealTimeCoarseClock::get().read_time().as_secs()
+}
+
+/// The status of a shared memory segment, which is reported by `IPC_STAT`.
+#[repr(C)]
+#[derive(Debug, Default, Clone, Copy, Pod)]
+pub struct shmid64_ds {
+    shm_perm: ipc64_perm,
+    shm_segsz: usize,
+    shm_atime: i64,
+    shm_dtime: i64,
+    shm_ctime: i64,
+    shm_cpid: i32,
+    shm_lpid: i32,
+    shm_nattch: u64,
+    __unused4: u64,
+    __unused5: u64,
+}
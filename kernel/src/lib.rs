@@ -65,6 +65,7 @@ pub mod net;
 pub mod prelude;
 mod process;
 mod sched;
+mod security;
 pub mod syscall;
 pub mod thread;
 pub mod time;
@@ -93,14 +94,21 @@ pub fn main() {
 
 pub fn init() {
     util::random::init();
-    driver::init();
     time::init();
+    sched::init();
+    // The threads of the threaded IRQ handlers, which the drivers have registered, can only be
+    // spawned after the scheduler is initialized.
+    thread::kernel_thread::init();
+    // The work queues should be initialized before any IRQ handlers use them to defer work.
+    thread::work_queue::init();
+    driver::init();
+    thread::profiler::init();
+    thread::hung_task::init();
+    vm::thp::init();
     #[cfg(target_arch = "x86_64")]
     net::init();
-    sched::init();
     fs::rootfs::init(boot_info().initramfs.expect("No initramfs found!")).unwrap();
     device::init().unwrap();
-    syscall::init();
     vdso::init();
     process::init();
 }
@@ -113,6 +121,7 @@ fn ap_init() {
         log::info!("Kernel idle thread for CPU #{} started.", cpu_id.as_usize());
         loop {
             Thread::yield_now();
+            ostd::task::scheduler::idle_current_cpu();
         }
     }
     let preempt_guard = ostd::task::disable_preempt();
@@ -127,13 +136,9 @@ fn ap_init() {
 
 fn init_thread() {
     println!("[kernel] Spawn init thread");
-    // Work queue should be initialized before interrupt is enabled,
-    // in case any irq handler uses work queue as bottom half
-    thread::work_queue::init();
     #[cfg(target_arch = "x86_64")]
     net::lazy_init();
     fs::lazy_init();
-    ipc::init();
     // driver::pci::virtio::block::block_device_test();
     let thread = ThreadOptions::new(|| {
         println!("[kernel] Hello world from kernel!");
@@ -156,6 +161,8 @@ fn init_thread() {
         // We don't have preemptive scheduler now.
         // The long running init thread should yield its own execution to allow other tasks to go on.
         Thread::yield_now();
+        // The init thread is also the idle thread of the BSP.
+        ostd::task::scheduler::idle_current_cpu();
     }
 
     // TODO: exit via qemu isa debug device should not be the only way.
@@ -1,12 +1,18 @@
 // SPDX-License-Identifier: MPL-2.0
 
-use super::sched::PollScheduler;
-use crate::net::socket::ip::{datagram::DatagramObserver, stream::StreamObserver};
+use super::{filter::IpFilter, sched::PollScheduler};
+use crate::net::socket::{
+    ip::{datagram::DatagramObserver, raw::RawIpTap, stream::StreamObserver},
+    packet::PacketTap,
+};
 
 pub struct BigtcpExt;
 
 impl aster_bigtcp::ext::Ext for BigtcpExt {
     type ScheduleNextPoll = PollScheduler;
+    type TapFrame = PacketTap;
+    type TapIpPacket = RawIpTap;
+    type FilterIpPacket = IpFilter;
 
     type TcpEventObserver = StreamObserver;
     type UdpEventObserver = DatagramObserver;
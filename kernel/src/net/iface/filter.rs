@@ -0,0 +1,162 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! The packet filter.
+//!
+//! The packet filter holds a list of rules for each netfilter-style hook. When a packet passes
+//! through a hook, the rules of the hook are checked in order, and the verdict of the first
+//! matching rule applies. If no rule matches, the packet is accepted.
+//!
+//! The rules are configured via the `/dev/netfilter` device.
+//
+// TODO: Support NAT, which rewrites the addresses and the ports of the packets, and the
+// connection tracking that it relies on.
+
+use core::sync::atomic::{AtomicUsize, Ordering};
+
+use aster_bigtcp::{
+    iface::{FilterHook, FilterIpPacket, FilterPacket, FilterVerdict},
+    wire::{IpCidr, IpProtocol, IpVersion},
+};
+use ostd::sync::LocalIrqDisabled;
+
+use super::get_iface_by_index;
+use crate::prelude::*;
+
+/// A rule of the packet filter.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FilterRule {
+    /// The hook where the rule is checked.
+    pub hook: FilterHook,
+    /// The index of the iface that the packets pass through, or zero to match all ifaces.
+    pub ifindex: u32,
+    /// The IP version of the packets, or `None` to match both IPv4 and IPv6.
+    pub ip_version: Option<IpVersion>,
+    /// The transport protocol of the packets, or `None` to match all protocols.
+    pub protocol: Option<IpProtocol>,
+    /// The subnet that contains the source addresses, or `None` to match all addresses.
+    pub src: Option<IpCidr>,
+    /// The subnet that contains the destination addresses, or `None` to match all addresses.
+    pub dst: Option<IpCidr>,
+    /// The source port, or `None` to match all ports.
+    pub src_port: Option<u16>,
+    /// The destination port, or `None` to match all ports.
+    pub dst_port: Option<u16>,
+    /// The verdict for the matching packets.
+    pub verdict: FilterVerdict,
+}
+
+/// The maximum number of the rules.
+const MAX_RULES: usize = 256;
+
+/// The rules of all the hooks.
+///
+/// The lock disables local IRQs because packets are filtered while the ifaces are being polled,
+/// which may happen in the IRQ context.
+static RULES: SpinLock<Vec<FilterRule>, LocalIrqDisabled> = SpinLock::new(Vec::new());
+
+/// The number of the rules.
+///
+/// This allows filtering packets without taking the lock if there are no rules, which is the
+/// common case.
+static NUM_RULES: AtomicUsize = AtomicUsize::new(0);
+
+/// Appends a rule to the packet filter.
+pub fn add_filter_rule(rule: FilterRule) -> Result<()> {
+    if rule.ifindex != 0 && get_iface_by_index(rule.ifindex).is_none() {
+        return_errno_with_message!(Errno::ENODEV, "the iface does not exist");
+    }
+
+    let is_version_mismatched = |cidr: Option<IpCidr>| match (rule.ip_version, cidr) {
+        (Some(ip_version), Some(cidr)) => cidr.address().version() != ip_version,
+        _ => false,
+    };
+    if is_version_mismatched(rule.src) || is_version_mismatched(rule.dst) {
+        return_errno_with_message!(
+            Errno::EINVAL,
+            "the addresses do not match the address family"
+        );
+    }
+
+    let mut rules = RULES.lock();
+    if rules.len() >= MAX_RULES {
+        return_errno_with_message!(Errno::ENOSPC, "too many filter rules");
+    }
+    rules.push(rule);
+    NUM_RULES.store(rules.len(), Ordering::Relaxed);
+
+    Ok(())
+}
+
+/// Removes all the rules of the hook from the packet filter.
+pub fn flush_filter_rules(hook: FilterHook) {
+    let mut rules = RULES.lock();
+    rules.retain(|rule| rule.hook != hook);
+    NUM_RULES.store(rules.len(), Ordering::Relaxed);
+}
+
+/// The packet filter of an iface.
+pub struct IpFilter {
+    ifindex: u32,
+}
+
+impl IpFilter {
+    pub(super) fn new(ifindex: u32) -> Self {
+        Self { ifindex }
+    }
+}
+
+impl FilterIpPacket for IpFilter {
+    fn filter_ip_packet(&self, hook: FilterHook, packet: &FilterPacket) -> FilterVerdict {
+        if NUM_RULES.load(Ordering::Relaxed) == 0 {
+            return FilterVerdict::Accept;
+        }
+
+        RULES
+            .lock()
+            .iter()
+            .find(|rule| rule.hook == hook && rule.matches(self.ifindex, packet))
+            .map_or(FilterVerdict::Accept, |rule| rule.verdict)
+    }
+}
+
+impl FilterRule {
+    fn matches(&self, ifindex: u32, packet: &FilterPacket) -> bool {
+        if self.ifindex != 0 && self.ifindex != ifindex {
+            return false;
+        }
+
+        if self
+            .ip_version
+            .is_some_and(|ip_version| packet.src_addr.version() != ip_version)
+        {
+            return false;
+        }
+
+        if self
+            .protocol
+            .is_some_and(|protocol| packet.protocol != protocol)
+        {
+            return false;
+        }
+
+        if self
+            .src
+            .is_some_and(|src| !src.contains_addr(&packet.src_addr))
+            || self
+                .dst
+                .is_some_and(|dst| !dst.contains_addr(&packet.dst_addr))
+        {
+            return false;
+        }
+
+        // A rule that specifies a port never matches packets without ports.
+        if self.src_port.is_none() && self.dst_port.is_none() {
+            return true;
+        }
+        let Some((src_port, dst_port)) = packet.ports else {
+            return false;
+        };
+        self.src_port.is_none_or(|port| port == src_port)
+            && self.dst_port.is_none_or(|port| port == dst_port)
+    }
+}
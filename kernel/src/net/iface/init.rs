@@ -1,31 +1,127 @@
 // SPDX-License-Identifier: MPL-2.0
 
 use alloc::{borrow::ToOwned, sync::Arc};
+use core::{
+    sync::atomic::{AtomicBool, AtomicU32, Ordering},
+    time::Duration,
+};
 
 use aster_bigtcp::device::WithDevice;
-use ostd::sync::LocalIrqDisabled;
-use spin::Once;
+use ostd::{
+    boot::boot_info,
+    sync::{LocalIrqDisabled, RwLock, WaitQueue},
+};
 
-use super::{poll::poll_ifaces, Iface};
-use crate::{net::iface::sched::PollScheduler, prelude::*};
+use super::{
+    filter::IpFilter,
+    poll::{poll_ifaces, spawn_background_poll_thread},
+    Iface,
+};
+use crate::{
+    kcmdline::{KCmdlineArg, ModuleArg},
+    net::{
+        iface::sched::PollScheduler,
+        socket::{ip::raw::RawIpTap, packet::PacketTap},
+    },
+    prelude::*,
+    thread::work_queue::{submit_work_item, work_item::WorkItem, WorkPriority},
+};
 
-pub static IFACES: Once<Vec<Arc<Iface>>> = Once::new();
+/// The ifaces along with their indexes.
+///
+/// The lock disables local IRQs because the ifaces are looked up in the IRQ handlers of the
+/// network devices.
+static IFACES: RwLock<Vec<(u32, Arc<Iface>)>, LocalIrqDisabled> = RwLock::new(Vec::new());
+
+/// The index of the virtio iface.
+///
+/// The iface indexes start from one.
+const VIRTIO_IFACE_INDEX: u32 = 1;
+
+/// The index of the loopback iface.
+const LOOPBACK_IFACE_INDEX: u32 = 2;
+
+/// The index of the next iface added by [`add_iface`].
+///
+/// Like Linux, the iface indexes are not reused after the ifaces are removed.
+static NEXT_IFACE_INDEX: AtomicU32 = AtomicU32::new(LOOPBACK_IFACE_INDEX + 1);
+
+/// Whether the virtio iface is configured by DHCP.
+static IS_DHCP_ENABLED: AtomicBool = AtomicBool::new(false);
+
+/// Returns the ifaces along with their indexes.
+///
+/// The ifaces that are added or removed after this function returns are not reflected.
+pub fn iter_ifaces_with_index() -> impl Iterator<Item = (u32, Arc<Iface>)> {
+    IFACES.read().clone().into_iter()
+}
+
+/// Gets the iface with the specified index.
+pub fn get_iface_by_index(index: u32) -> Option<Arc<Iface>> {
+    IFACES
+        .read()
+        .iter()
+        .find(|(iface_index, _)| *iface_index == index)
+        .map(|(_, iface)| iface.clone())
+}
+
+/// Returns whether the iface with the specified index is the loopback iface.
+pub fn is_loopback_iface(index: u32) -> bool {
+    index == LOOPBACK_IFACE_INDEX
+}
+
+/// Adds an iface that is created by the closure with the index allocated to it.
+///
+/// The iface is polled in the background until it is removed by [`remove_iface`].
+pub fn add_iface(new_iface: impl FnOnce(u32) -> Arc<Iface>) -> (u32, Arc<Iface>) {
+    let index = NEXT_IFACE_INDEX.fetch_add(1, Ordering::Relaxed);
+    let iface = new_iface(index);
+
+    IFACES.write().push((index, iface.clone()));
+    spawn_background_poll_thread(iface.clone());
+
+    (index, iface)
+}
+
+/// Removes the iface with the specified index.
+///
+/// The sockets that have been bound to the iface can still use it, but the iface can no longer
+/// be found by the routing table.
+pub fn remove_iface(index: u32) {
+    let mut ifaces = IFACES.write();
+    let Some(pos) = ifaces
+        .iter()
+        .position(|(iface_index, _)| *iface_index == index)
+    else {
+        return;
+    };
+    let (_, iface) = ifaces.remove(pos);
+    drop(ifaces);
+
+    iface.sched_poll().stop();
+}
 
 pub fn init() {
-    IFACES.call_once(|| {
-        let iface_virtio = new_virtio();
-        let iface_loopback = new_loopback();
-        vec![iface_virtio, iface_loopback]
-    });
+    *IFACES.write() = vec![
+        (VIRTIO_IFACE_INDEX, new_virtio()),
+        (LOOPBACK_IFACE_INDEX, new_loopback()),
+    ];
 
+    // TODO: further check that the irq num is the same as iface's irq num
+    let poll_virtio = || {
+        let iface_virtio = get_iface_by_index(VIRTIO_IFACE_INDEX).unwrap();
+        iface_virtio.poll();
+    };
+    // The send callbacks are called in the IRQ handlers, where polling the iface is too heavy,
+    // so it is deferred to the workers. The receive callbacks are called in the softirq context.
+    let poll_work = WorkItem::new(Box::new(poll_virtio));
     for (name, _) in aster_network::all_devices() {
-        let callback = || {
-            // TODO: further check that the irq num is the same as iface's irq num
-            let iface_virtio = &IFACES.get().unwrap()[0];
-            iface_virtio.poll();
-        };
-        aster_network::register_recv_callback(&name, callback);
-        aster_network::register_send_callback(&name, callback);
+        aster_network::register_recv_callback(&name, poll_virtio);
+
+        let poll_work = poll_work.clone();
+        aster_network::register_send_callback(&name, move || {
+            submit_work_item(poll_work.clone(), WorkPriority::High);
+        });
     }
 
     poll_ifaces();
@@ -33,16 +129,23 @@ pub fn init() {
 
 fn new_virtio() -> Arc<Iface> {
     use aster_bigtcp::{
-        iface::EtherIface,
-        wire::{EthernetAddress, Ipv4Address, Ipv4Cidr},
+        iface::{EtherIface, Ipv4Config},
+        wire::{EthernetAddress, Ipv4Address, Ipv4Cidr, Ipv6Address, Ipv6Cidr},
     };
     use aster_network::AnyNetworkDevice;
     use aster_virtio::device::network::DEVICE_NAME;
 
+    // The static IPv4 configuration, which is used only if DHCP is disabled, follows the default
+    // one of the QEMU user-mode network.
     const VIRTIO_ADDRESS: Ipv4Address = Ipv4Address::new(10, 0, 2, 15);
     const VIRTIO_ADDRESS_PREFIX_LEN: u8 = 24; // mask: 255.255.255.0
     const VIRTIO_GATEWAY: Ipv4Address = Ipv4Address::new(10, 0, 2, 2);
 
+    // The IPv6 configuration follows the default one of the QEMU user-mode network.
+    const VIRTIO_IPV6_ADDRESS: Ipv6Address = Ipv6Address::new(0xfec0, 0, 0, 0, 0, 0, 0, 0x15);
+    const VIRTIO_IPV6_ADDRESS_PREFIX_LEN: u8 = 64;
+    const VIRTIO_IPV6_GATEWAY: Ipv6Address = Ipv6Address::new(0xfec0, 0, 0, 0, 0, 0, 0, 2);
+
     let virtio_net = aster_network::get_device(DEVICE_NAME).unwrap();
 
     let ether_addr = virtio_net.lock().mac_addr().0;
@@ -61,26 +164,109 @@ fn new_virtio() -> Arc<Iface> {
         }
     }
 
-    EtherIface::new(
+    let ipv4_config = if is_dhcp_enabled_by_kcmdline() {
+        IS_DHCP_ENABLED.store(true, Ordering::Relaxed);
+        Ipv4Config::Dhcp
+    } else {
+        Ipv4Config::Static {
+            ipv4_cidr: Ipv4Cidr::new(VIRTIO_ADDRESS, VIRTIO_ADDRESS_PREFIX_LEN),
+            gateway: VIRTIO_GATEWAY,
+        }
+    };
+
+    let iface: Arc<Iface> = EtherIface::new(
         Wrapper(virtio_net),
         EthernetAddress(ether_addr),
-        Ipv4Cidr::new(VIRTIO_ADDRESS, VIRTIO_ADDRESS_PREFIX_LEN),
-        VIRTIO_GATEWAY,
+        ipv4_config,
         "virtio".to_owned(),
         PollScheduler::new(),
-    )
+        PacketTap::new(VIRTIO_IFACE_INDEX, EthernetAddress(ether_addr)),
+        RawIpTap,
+        IpFilter::new(VIRTIO_IFACE_INDEX),
+    );
+
+    iface
+        .add_ipv6_cidr(Ipv6Cidr::new(
+            VIRTIO_IPV6_ADDRESS,
+            VIRTIO_IPV6_ADDRESS_PREFIX_LEN,
+        ))
+        .unwrap();
+    iface
+        .add_ipv6_route(
+            Ipv6Cidr::new(Ipv6Address::UNSPECIFIED, 0),
+            VIRTIO_IPV6_GATEWAY,
+        )
+        .unwrap();
+
+    iface
+}
+
+/// Returns whether DHCP is enabled by the kernel command line.
+///
+/// DHCP is enabled unless `net.dhcp=off` is specified.
+fn is_dhcp_enabled_by_kcmdline() -> bool {
+    let karg: KCmdlineArg = boot_info().kernel_cmdline.as_str().into();
+    let Some(args) = karg.get_module_args("net") else {
+        return true;
+    };
+
+    !args.iter().any(|arg| {
+        matches!(arg, ModuleArg::KeyVal(key, value)
+            if key.as_bytes() == b"dhcp" && value.as_bytes() == b"off")
+    })
+}
+
+/// Waits for the virtio iface to obtain a DHCP lease, if DHCP is enabled.
+///
+/// This should be called after the background polling threads are spawned, so that the DHCP
+/// messages can be sent and received. If the lease cannot be obtained in time, the DHCP client
+/// keeps trying in the background.
+pub(super) fn wait_for_dhcp_lease() {
+    const DHCP_TIMEOUT: Duration = Duration::from_secs(5);
+    const CHECK_INTERVAL: Duration = Duration::from_millis(10);
+
+    if !IS_DHCP_ENABLED.load(Ordering::Relaxed) {
+        return;
+    }
+
+    let iface_virtio = get_iface_by_index(VIRTIO_IFACE_INDEX).unwrap();
+    let wait_queue = WaitQueue::new();
+
+    let mut elapsed = Duration::ZERO;
+    while elapsed < DHCP_TIMEOUT {
+        if let Some(lease) = iface_virtio.dhcp_lease() {
+            info!(
+                "[DHCP] {} is configured with {}",
+                iface_virtio.name(),
+                lease.ipv4_cidr
+            );
+            return;
+        }
+
+        let _ = wait_queue.wait_until_or_timeout(|| -> Option<()> { None }, &CHECK_INTERVAL);
+        elapsed += CHECK_INTERVAL;
+    }
+
+    warn!(
+        "[DHCP] {} is not configured after {:?}",
+        iface_virtio.name(),
+        DHCP_TIMEOUT
+    );
 }
 
 fn new_loopback() -> Arc<Iface> {
     use aster_bigtcp::{
         device::{Loopback, Medium},
         iface::IpIface,
-        wire::{Ipv4Address, Ipv4Cidr},
+        wire::{Ipv4Address, Ipv4Cidr, Ipv6Address, Ipv6Cidr},
     };
 
     const LOOPBACK_ADDRESS: Ipv4Address = Ipv4Address::new(127, 0, 0, 1);
     const LOOPBACK_ADDRESS_PREFIX_LEN: u8 = 8; // mask: 255.0.0.0
 
+    const LOOPBACK_IPV6_ADDRESS: Ipv6Address = Ipv6Address::LOCALHOST;
+    const LOOPBACK_IPV6_ADDRESS_PREFIX_LEN: u8 = 128;
+
     struct Wrapper(Mutex<Loopback>);
 
     impl WithDevice for Wrapper {
@@ -95,10 +281,21 @@ fn new_loopback() -> Arc<Iface> {
         }
     }
 
-    IpIface::new(
+    let iface: Arc<Iface> = IpIface::new(
         Wrapper(Mutex::new(Loopback::new(Medium::Ip))),
-        Ipv4Cidr::new(LOOPBACK_ADDRESS, LOOPBACK_ADDRESS_PREFIX_LEN),
+        Some(Ipv4Cidr::new(LOOPBACK_ADDRESS, LOOPBACK_ADDRESS_PREFIX_LEN)),
         "lo".to_owned(),
         PollScheduler::new(),
-    ) as _
+        RawIpTap,
+        IpFilter::new(LOOPBACK_IFACE_INDEX),
+    );
+
+    iface
+        .add_ipv6_cidr(Ipv6Cidr::new(
+            LOOPBACK_IPV6_ADDRESS,
+            LOOPBACK_IPV6_ADDRESS_PREFIX_LEN,
+        ))
+        .unwrap();
+
+    iface
 }
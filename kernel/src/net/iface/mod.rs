@@ -1,12 +1,20 @@
 // SPDX-License-Identifier: MPL-2.0
 
 mod ext;
+mod filter;
 mod init;
 mod poll;
+mod route;
 mod sched;
+mod tun;
 
-pub use init::{init, IFACES};
+pub use filter::{add_filter_rule, flush_filter_rules, FilterRule};
+pub use init::{
+    add_iface, get_iface_by_index, init, is_loopback_iface, iter_ifaces_with_index, remove_iface,
+};
 pub use poll::lazy_init;
+pub use route::{add_gateway_route, lookup_route, routes, Route};
+pub use tun::{TunIface, TunKind};
 
 pub type Iface = dyn aster_bigtcp::iface::Iface<ext::BigtcpExt>;
 pub type BoundPort = aster_bigtcp::iface::BoundPort<ext::BigtcpExt>;
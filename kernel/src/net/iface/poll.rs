@@ -6,24 +6,25 @@ use core::time::Duration;
 use log::trace;
 use ostd::timer::Jiffies;
 
-use super::{Iface, IFACES};
+use super::{init::wait_for_dhcp_lease, iter_ifaces_with_index, Iface};
 use crate::{sched::priority::Priority, thread::kernel_thread::ThreadOptions, WaitTimeout};
 
 pub fn lazy_init() {
-    for iface in IFACES.get().unwrap() {
-        spawn_background_poll_thread(iface.clone());
+    for (_, iface) in iter_ifaces_with_index() {
+        spawn_background_poll_thread(iface);
     }
+
+    wait_for_dhcp_lease();
 }
 
 pub(super) fn poll_ifaces() {
-    let ifaces = IFACES.get().unwrap();
-
-    for iface in ifaces.iter() {
+    for (_, iface) in iter_ifaces_with_index() {
         iface.poll();
     }
 }
 
-fn spawn_background_poll_thread(iface: Arc<Iface>) {
+/// Spawns a thread that polls the iface in the background until the iface is removed.
+pub(super) fn spawn_background_poll_thread(iface: Arc<Iface>) {
     let task_fn = move || {
         trace!("spawn background poll thread for {}", iface.name());
 
@@ -34,9 +35,18 @@ fn spawn_background_poll_thread(iface: Arc<Iface>) {
             let next_poll_at_ms = if let Some(next_poll_at_ms) = sched_poll.next_poll_at_ms() {
                 next_poll_at_ms
             } else {
-                wait_queue.wait_until(|| sched_poll.next_poll_at_ms())
+                wait_queue.wait_until(|| {
+                    sched_poll
+                        .next_poll_at_ms()
+                        .or_else(|| sched_poll.is_stopped().then_some(0))
+                })
             };
 
+            if sched_poll.is_stopped() {
+                trace!("stop background poll thread for {}", iface.name());
+                break;
+            }
+
             let now_as_ms = Jiffies::elapsed().as_duration().as_millis() as u64;
 
             // FIXME: Ideally, we should perform the `poll` just before `next_poll_at_ms`.
@@ -54,9 +64,12 @@ fn spawn_background_poll_thread(iface: Arc<Iface>) {
 
             let duration = Duration::from_millis(next_poll_at_ms - now_as_ms);
             let _ = wait_queue.wait_until_or_timeout(
-                // If `sched_poll.next_poll_at_ms()` changes to an earlier time, we will end the
-                // waiting.
-                || (sched_poll.next_poll_at_ms()? < next_poll_at_ms).then_some(()),
+                // If `sched_poll.next_poll_at_ms()` changes to an earlier time, or if the iface is
+                // removed, we will end the waiting.
+                || {
+                    (sched_poll.is_stopped() || sched_poll.next_poll_at_ms()? < next_poll_at_ms)
+                        .then_some(())
+                },
                 &duration,
             );
         }
@@ -64,6 +77,7 @@ fn spawn_background_poll_thread(iface: Arc<Iface>) {
 
     // FIXME: remove the use of real-time priority.
     ThreadOptions::new(task_fn)
+        .name("iface_poll")
         .priority(Priority::default_real_time())
         .spawn();
 }
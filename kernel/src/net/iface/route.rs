@@ -0,0 +1,185 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! The routing table.
+//!
+//! The routing table selects the outgoing iface for a destination among all the ifaces. Among
+//! the routes whose destination subnets contain the destination, the route with the longest
+//! prefix is selected, and the route with the lowest metric wins if there is a tie.
+//!
+//! Each iface resolves the next hop by itself, so the routes are derived from the addresses and
+//! the gateway routes of the ifaces, which may be changed by DHCP. Only the metrics of the gateway
+//! routes are stored here.
+
+use core::cmp::Reverse;
+
+use aster_bigtcp::{
+    errors::IfaceConfigError,
+    wire::{IpAddress, IpCidr, Ipv6Address, Ipv6Cidr},
+};
+
+use super::{get_iface_by_index, iter_ifaces_with_index, Iface};
+use crate::prelude::*;
+
+/// A route in the routing table.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Route {
+    /// The destination subnet.
+    pub dst: IpCidr,
+    /// The gateway, or `None` if the destination subnet is directly connected.
+    pub gateway: Option<IpAddress>,
+    /// The preferred source address.
+    pub pref_src: Option<IpAddress>,
+    /// The index of the outgoing iface.
+    pub ifindex: u32,
+    /// The metric, where a lower value means a higher priority.
+    pub metric: u32,
+}
+
+/// The metrics of the gateway routes, as tuples of the iface index, the destination, and the
+/// metric.
+///
+/// The gateway routes that are not listed here have a metric of zero.
+static GATEWAY_METRICS: RwLock<Vec<(u32, IpCidr, u32)>> = RwLock::new(Vec::new());
+
+/// Returns all the routes, including the routes to the directly connected subnets.
+pub fn routes() -> Vec<Route> {
+    let mut routes = Vec::new();
+
+    let gateway_metrics = GATEWAY_METRICS.read();
+    let metric_of = |ifindex: u32, dst: IpCidr| {
+        gateway_metrics
+            .iter()
+            .find(|(index, cidr, _)| *index == ifindex && *cidr == dst)
+            .map_or(0, |(_, _, metric)| *metric)
+    };
+
+    for (ifindex, iface) in iter_ifaces_with_index() {
+        routes.extend(ip_cidrs(&iface).into_iter().map(|ip_cidr| Route {
+            dst: network_of(ip_cidr),
+            gateway: None,
+            pref_src: Some(ip_cidr.address()),
+            ifindex,
+            metric: 0,
+        }));
+
+        let ipv4_routes = iface
+            .ipv4_routes()
+            .into_iter()
+            .map(|(dst, gateway)| (IpCidr::Ipv4(dst), IpAddress::Ipv4(gateway)));
+        let ipv6_routes = iface
+            .ipv6_routes()
+            .into_iter()
+            .map(|(dst, gateway)| (IpCidr::Ipv6(dst), IpAddress::Ipv6(gateway)));
+        routes.extend(ipv4_routes.chain(ipv6_routes).map(|(dst, gateway)| Route {
+            dst,
+            gateway: Some(gateway),
+            pref_src: None,
+            ifindex,
+            metric: metric_of(ifindex, dst),
+        }));
+    }
+
+    routes
+}
+
+/// Adds a route to the destination via the gateway of the iface.
+///
+/// FIXME: An iface can have only one route to each destination subnet, even if the metrics are
+/// different, because the iface picks the next hop by the destination.
+pub fn add_gateway_route(dst: IpCidr, gateway: IpAddress, ifindex: u32, metric: u32) -> Result<()> {
+    let Some(iface) = get_iface_by_index(ifindex) else {
+        return_errno_with_message!(Errno::ENODEV, "the iface does not exist");
+    };
+
+    let mut gateway_metrics = GATEWAY_METRICS.write();
+
+    let result = match (dst, gateway) {
+        (IpCidr::Ipv4(ipv4_cidr), IpAddress::Ipv4(ipv4_gateway)) => {
+            iface.add_ipv4_route(ipv4_cidr, ipv4_gateway)
+        }
+        (IpCidr::Ipv6(ipv6_cidr), IpAddress::Ipv6(ipv6_gateway)) => {
+            iface.add_ipv6_route(ipv6_cidr, ipv6_gateway)
+        }
+        _ => return_errno_with_message!(
+            Errno::EINVAL,
+            "the gateway and the destination have different IP versions"
+        ),
+    };
+    result.map_err(|err| match err {
+        IfaceConfigError::Exists => Error::with_message(Errno::EEXIST, "the route already exists"),
+        IfaceConfigError::Full => {
+            Error::with_message(Errno::ENOSPC, "the iface cannot have more routes")
+        }
+    })?;
+
+    gateway_metrics.retain(|(index, cidr, _)| *index != ifindex || *cidr != dst);
+    if metric != 0 {
+        gateway_metrics.push((ifindex, dst, metric));
+    }
+
+    Ok(())
+}
+
+/// Looks up the route to the destination.
+///
+/// This method returns the outgoing iface and the source address that should be used to reach
+/// the destination, or `None` if the destination is unreachable.
+pub fn lookup_route(dst: &IpAddress) -> Option<(Arc<Iface>, IpAddress)> {
+    // The local addresses are reachable via the ifaces that own them.
+    for (_, iface) in iter_ifaces_with_index() {
+        if ip_cidrs(&iface)
+            .iter()
+            .any(|ip_cidr| ip_cidr.address() == *dst)
+        {
+            return Some((iface, *dst));
+        }
+    }
+
+    let route = routes()
+        .into_iter()
+        .filter(|route| route.dst.contains_addr(dst))
+        .min_by_key(|route| (Reverse(route.dst.prefix_len()), route.metric, route.ifindex))?;
+    let iface = get_iface_by_index(route.ifindex)?;
+
+    if let Some(pref_src) = route.pref_src {
+        return Some((iface, pref_src));
+    }
+
+    // Prefer the address in the same subnet as the gateway.
+    let ip_cidrs = ip_cidrs(&iface);
+    let src = ip_cidrs
+        .iter()
+        .find(|ip_cidr| {
+            route
+                .gateway
+                .is_some_and(|gateway| ip_cidr.contains_addr(&gateway))
+        })
+        .or_else(|| {
+            ip_cidrs
+                .iter()
+                .find(|ip_cidr| ip_cidr.address().version() == dst.version())
+        })?
+        .address();
+
+    Some((iface, src))
+}
+
+/// Returns all the IPv4 and IPv6 addresses of the iface, along with their prefix lengths.
+fn ip_cidrs(iface: &Iface) -> Vec<IpCidr> {
+    let ipv4_cidrs = iface.ipv4_cidrs().into_iter().map(IpCidr::Ipv4);
+    let ipv6_cidrs = iface.ipv6_cidrs().into_iter().map(IpCidr::Ipv6);
+    ipv4_cidrs.chain(ipv6_cidrs).collect()
+}
+
+/// Returns the subnet that contains the address.
+fn network_of(ip_cidr: IpCidr) -> IpCidr {
+    match ip_cidr {
+        IpCidr::Ipv4(ipv4_cidr) => IpCidr::Ipv4(ipv4_cidr.network()),
+        IpCidr::Ipv6(ipv6_cidr) => {
+            let prefix_len = ipv6_cidr.prefix_len();
+            let mask = u128::MAX.checked_shl(128 - prefix_len as u32).unwrap_or(0);
+            let network = Ipv6Address::from(u128::from(ipv6_cidr.address()) & mask);
+            IpCidr::Ipv6(Ipv6Cidr::new(network, prefix_len))
+        }
+    }
+}
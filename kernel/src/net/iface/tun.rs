@@ -0,0 +1,315 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! Virtual ifaces whose packets are read and written by the user space.
+//!
+//! A TUN iface exchanges IP packets with the user space, while a TAP iface exchanges Ethernet
+//! frames. The ifaces are created via the `/dev/net/tun` device.
+
+use alloc::format;
+
+use aster_bigtcp::{
+    device::{self, DeviceCapabilities, FilterMulticast, Medium, NotifyDevice, WithDevice},
+    iface::{EtherIface, IpIface, Ipv4Config},
+    time::Instant,
+    wire::EthernetAddress,
+};
+use ostd::sync::LocalIrqDisabled;
+
+use super::{
+    add_iface, filter::IpFilter, iter_ifaces_with_index, remove_iface, sched::PollScheduler, Iface,
+};
+use crate::{
+    events::IoEvents,
+    net::socket::{ip::raw::RawIpTap, packet::PacketTap},
+    prelude::*,
+    process::signal::Pollee,
+    util::random::getrandom,
+};
+
+/// The kind of a virtual iface.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TunKind {
+    /// An iface that exchanges IP packets.
+    Tun,
+    /// An iface that exchanges Ethernet frames.
+    Tap,
+}
+
+/// A virtual iface that is backed by a file in the user space.
+pub struct TunIface {
+    index: u32,
+    kind: TunKind,
+    iface: Arc<Iface>,
+    queue: Arc<SpinLock<TunQueue, LocalIrqDisabled>>,
+}
+
+/// The MTU of the virtual ifaces.
+const TUN_MTU: usize = 1500;
+
+/// The length of the Ethernet header.
+const ETHER_HEADER_LEN: usize = 14;
+
+/// The maximum number of the packets that are sent by the iface but not yet read by the user
+/// space.
+///
+/// Like Linux, more packets are dropped silently.
+const MAX_TX_QUEUE_LEN: usize = 500;
+
+/// The maximum number of the packets that are written by the user space but not yet received by
+/// the iface.
+const MAX_RX_QUEUE_LEN: usize = 500;
+
+/// The lock that serializes the allocation of the iface names.
+static NAME_LOCK: Mutex<()> = Mutex::new(());
+
+impl TunIface {
+    /// Creates a new virtual iface.
+    ///
+    /// If the name is empty, a name is allocated according to the kind of the iface. If the name
+    /// contains `%d`, it is replaced with the first unit number that is not used.
+    ///
+    /// The pollee is notified with [`IoEvents::IN`] when the iface sends a packet.
+    pub fn new(name: &str, kind: TunKind, pollee: Pollee) -> Result<Arc<Self>> {
+        let _guard = NAME_LOCK.lock();
+
+        let name = alloc_name(name, kind)?;
+
+        let medium = match kind {
+            TunKind::Tun => Medium::Ip,
+            TunKind::Tap => Medium::Ethernet,
+        };
+        let queue = Arc::new(SpinLock::new(TunQueue {
+            medium,
+            rx_queue: VecDeque::new(),
+            tx_queue: VecDeque::new(),
+            pollee,
+        }));
+
+        let ether_addr = match kind {
+            TunKind::Tun => None,
+            TunKind::Tap => Some(random_ether_addr()),
+        };
+
+        let driver = TunDriver(queue.clone());
+        let (index, iface) = add_iface(|index| match ether_addr {
+            None => IpIface::new(
+                driver,
+                None,
+                name,
+                PollScheduler::new(),
+                RawIpTap,
+                IpFilter::new(index),
+            ) as Arc<Iface>,
+            Some(ether_addr) => EtherIface::new(
+                driver,
+                ether_addr,
+                Ipv4Config::Unconfigured,
+                name,
+                PollScheduler::new(),
+                PacketTap::new(index, ether_addr),
+                RawIpTap,
+                IpFilter::new(index),
+            ) as Arc<Iface>,
+        });
+
+        Ok(Arc::new(Self {
+            index,
+            kind,
+            iface,
+            queue,
+        }))
+    }
+
+    /// Returns the index of the iface.
+    pub fn index(&self) -> u32 {
+        self.index
+    }
+
+    /// Returns the kind of the iface.
+    pub fn kind(&self) -> TunKind {
+        self.kind
+    }
+
+    /// Returns the name of the iface.
+    pub fn name(&self) -> &str {
+        self.iface.name()
+    }
+
+    /// Returns the maximum length of the packets that can be written to the iface.
+    pub fn max_packet_len(&self) -> usize {
+        match self.kind {
+            TunKind::Tun => TUN_MTU,
+            TunKind::Tap => TUN_MTU + ETHER_HEADER_LEN,
+        }
+    }
+
+    /// Returns whether there are packets sent by the iface.
+    pub fn has_packet(&self) -> bool {
+        !self.queue.lock().tx_queue.is_empty()
+    }
+
+    /// Takes the packet that was first sent by the iface.
+    pub fn pop_packet(&self) -> Option<Vec<u8>> {
+        let mut queue = self.queue.lock();
+
+        let packet = queue.tx_queue.pop_front();
+        if queue.tx_queue.is_empty() {
+            queue.pollee.invalidate();
+        }
+
+        packet
+    }
+
+    /// Delivers a packet to the iface, as if the packet is received from the network.
+    pub fn push_packet(&self, packet: Vec<u8>) -> Result<()> {
+        let mut queue = self.queue.lock();
+        if queue.rx_queue.len() >= MAX_RX_QUEUE_LEN {
+            return_errno_with_message!(Errno::EAGAIN, "the receive queue is full");
+        }
+        queue.rx_queue.push_back(packet);
+        drop(queue);
+
+        self.iface.poll();
+
+        Ok(())
+    }
+}
+
+impl Drop for TunIface {
+    fn drop(&mut self) {
+        remove_iface(self.index);
+    }
+}
+
+/// Allocates a name that is not used by other ifaces.
+fn alloc_name(name: &str, kind: TunKind) -> Result<String> {
+    /// The maximum length of the iface names, including the trailing null byte.
+    const IFNAMSIZ: usize = 16;
+
+    let template = if name.is_empty() {
+        match kind {
+            TunKind::Tun => "tun%d",
+            TunKind::Tap => "tap%d",
+        }
+    } else {
+        name
+    };
+    if template.len() >= IFNAMSIZ {
+        return_errno_with_message!(Errno::EINVAL, "the iface name is too long");
+    }
+
+    let is_used = |name: &str| iter_ifaces_with_index().any(|(_, iface)| iface.name() == name);
+
+    if !template.contains("%d") {
+        if is_used(template) {
+            return_errno_with_message!(Errno::EBUSY, "the iface name is already used");
+        }
+        return Ok(template.to_string());
+    }
+
+    // Like Linux, the number of the ifaces that share one template is limited.
+    const MAX_UNITS: usize = 1000;
+
+    (0..MAX_UNITS)
+        .map(|unit| template.replacen("%d", &format!("{}", unit), 1))
+        .find(|name| name.len() < IFNAMSIZ && !is_used(name))
+        .ok_or_else(|| Error::with_message(Errno::ENFILE, "no iface name is available"))
+}
+
+/// Generates a random, locally administered, unicast Ethernet address.
+fn random_ether_addr() -> EthernetAddress {
+    let mut bytes = [0u8; 6];
+    getrandom(&mut bytes);
+    bytes[0] = (bytes[0] & !0x01) | 0x02;
+
+    EthernetAddress(bytes)
+}
+
+/// The queues of the packets that pass through a virtual iface.
+struct TunQueue {
+    medium: Medium,
+    /// The packets written by the user space and to be received by the iface.
+    rx_queue: VecDeque<Vec<u8>>,
+    /// The packets sent by the iface and to be read by the user space.
+    tx_queue: VecDeque<Vec<u8>>,
+    pollee: Pollee,
+}
+
+struct TunDriver(Arc<SpinLock<TunQueue, LocalIrqDisabled>>);
+
+impl WithDevice for TunDriver {
+    type Device = TunQueue;
+
+    fn with<F, R>(&self, f: F) -> R
+    where
+        F: FnOnce(&mut Self::Device) -> R,
+    {
+        let mut queue = self.0.lock();
+        f(&mut queue)
+    }
+}
+
+impl device::Device for TunQueue {
+    type RxToken<'a> = TunRxToken;
+    type TxToken<'a> = TunTxToken<'a>;
+
+    fn receive(&mut self, _timestamp: Instant) -> Option<(Self::RxToken<'_>, Self::TxToken<'_>)> {
+        let packet = self.rx_queue.pop_front()?;
+        Some((TunRxToken(packet), TunTxToken(self)))
+    }
+
+    fn transmit(&mut self, _timestamp: Instant) -> Option<Self::TxToken<'_>> {
+        Some(TunTxToken(self))
+    }
+
+    fn capabilities(&self) -> DeviceCapabilities {
+        let mut caps = DeviceCapabilities::default();
+        caps.medium = self.medium;
+        caps.max_transmission_unit = match self.medium {
+            Medium::Ethernet => TUN_MTU + ETHER_HEADER_LEN,
+            _ => TUN_MTU,
+        };
+        caps
+    }
+}
+
+impl NotifyDevice for TunQueue {
+    fn notify_poll_end(&mut self) {}
+}
+
+impl FilterMulticast for TunQueue {
+    fn set_multicast_filter(&mut self, _ether_addrs: &[EthernetAddress]) {
+        // The user space receives all the frames, so there is no filter to program.
+    }
+}
+
+struct TunRxToken(Vec<u8>);
+
+impl device::RxToken for TunRxToken {
+    fn consume<R, F>(self, f: F) -> R
+    where
+        F: FnOnce(&[u8]) -> R,
+    {
+        f(&self.0)
+    }
+}
+
+struct TunTxToken<'a>(&'a mut TunQueue);
+
+impl device::TxToken for TunTxToken<'_> {
+    fn consume<R, F>(self, len: usize, f: F) -> R
+    where
+        F: FnOnce(&mut [u8]) -> R,
+    {
+        let mut buffer = vec![0u8; len];
+        let res = f(&mut buffer);
+
+        let queue = self.0;
+        if queue.tx_queue.len() < MAX_TX_QUEUE_LEN {
+            queue.tx_queue.push_back(buffer);
+            queue.pollee.notify(IoEvents::IN);
+        }
+
+        res
+    }
+}
@@ -1,6 +1,6 @@
 // SPDX-License-Identifier: MPL-2.0
 
-use aster_bigtcp::wire::{IpAddress, IpEndpoint, Ipv4Address};
+use aster_bigtcp::wire::{IpAddress, IpEndpoint, IpVersion, Ipv4Address, Ipv6Address};
 
 use crate::{net::socket::SocketAddr, prelude::*, return_errno_with_message};
 
@@ -10,6 +10,7 @@ impl TryFrom<SocketAddr> for IpEndpoint {
     fn try_from(value: SocketAddr) -> Result<Self> {
         match value {
             SocketAddr::IPv4(addr, port) => Ok(IpEndpoint::new(addr.into(), port)),
+            SocketAddr::IPv6(addr, port) => Ok(IpEndpoint::new(addr.into(), port)),
             _ => return_errno_with_message!(
                 Errno::EAFNOSUPPORT,
                 "the address is in an unsupported address family"
@@ -23,15 +24,39 @@ impl From<IpEndpoint> for SocketAddr {
         let port = endpoint.port;
         match endpoint.addr {
             IpAddress::Ipv4(addr) => SocketAddr::IPv4(addr, port),
-            // TODO: support IPv6
+            IpAddress::Ipv6(addr) => SocketAddr::IPv6(addr, port),
         }
     }
 }
 
-/// A local endpoint, which indicates that the local endpoint is unspecified.
+/// Converts the socket address to an IP endpoint of the specified IP version.
+///
+/// This method returns `Err(EAFNOSUPPORT)` if the address family of the socket address does not
+/// match the IP version of the socket.
+//
+// TODO: Support IPv4-mapped IPv6 addresses (e.g., `::ffff:127.0.0.1`) for IPv6 sockets.
+pub(super) fn to_endpoint(socket_addr: SocketAddr, ip_version: IpVersion) -> Result<IpEndpoint> {
+    let endpoint = IpEndpoint::try_from(socket_addr)?;
+
+    if endpoint.addr.version() != ip_version {
+        return_errno_with_message!(
+            Errno::EAFNOSUPPORT,
+            "the address family does not match the socket"
+        );
+    }
+
+    Ok(endpoint)
+}
+
+/// Returns a local endpoint, which indicates that the local endpoint is unspecified.
 ///
 /// According to the Linux man pages and the Linux implementation, `getsockname()` will _not_ fail
 /// even if the socket is unbound. Instead, it will return an unspecified socket address. This
 /// unspecified endpoint helps with that.
-pub(super) const UNSPECIFIED_LOCAL_ENDPOINT: IpEndpoint =
-    IpEndpoint::new(IpAddress::Ipv4(Ipv4Address::UNSPECIFIED), 0);
+pub(super) const fn unspecified_local_endpoint(ip_version: IpVersion) -> IpEndpoint {
+    let addr = match ip_version {
+        IpVersion::Ipv4 => IpAddress::Ipv4(Ipv4Address::UNSPECIFIED),
+        IpVersion::Ipv6 => IpAddress::Ipv6(Ipv6Address::UNSPECIFIED),
+    };
+    IpEndpoint::new(addr, 0)
+}
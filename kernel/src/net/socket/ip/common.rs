@@ -7,45 +7,50 @@ use aster_bigtcp::{
 };
 
 use crate::{
-    net::iface::{BoundPort, Iface, IFACES},
+    net::iface::{iter_ifaces_with_index, lookup_route, BoundPort, Iface},
     prelude::*,
+    process::{credentials::capabilities::CapSet, posix_thread::AsPosixThread},
 };
 
 pub(super) fn get_iface_to_bind(ip_addr: &IpAddress) -> Option<Arc<Iface>> {
-    let ifaces = IFACES.get().unwrap();
-    let IpAddress::Ipv4(ipv4_addr) = ip_addr;
-    ifaces
-        .iter()
-        .find(|iface| {
-            if let Some(iface_ipv4_addr) = iface.ipv4_addr() {
-                iface_ipv4_addr == *ipv4_addr
-            } else {
-                false
-            }
-        })
-        .map(Clone::clone)
+    // A socket can be bound to a multicast address to receive only the packets sent to the
+    // multicast group. The packets are received from the iface selected by the routing table,
+    // which is also the default iface to join the multicast group.
+    if ip_addr.is_multicast() {
+        return lookup_route(ip_addr).map(|(iface, _)| iface);
+    }
+
+    iter_ifaces_with_index()
+        .find(|(_, iface)| has_ip_addr(iface, ip_addr))
+        .map(|(_, iface)| iface)
 }
 
-/// Get a suitable iface to deal with sendto/connect request if the socket is not bound to an iface.
-/// If the remote address is the same as that of some iface, we will use the iface.
-/// Otherwise, we will use a default interface.
-fn get_ephemeral_iface(remote_ip_addr: &IpAddress) -> Arc<Iface> {
-    let ifaces = IFACES.get().unwrap();
-    let IpAddress::Ipv4(remote_ipv4_addr) = remote_ip_addr;
-    if let Some(iface) = ifaces.iter().find(|iface| {
-        if let Some(iface_ipv4_addr) = iface.ipv4_addr() {
-            iface_ipv4_addr == *remote_ipv4_addr
-        } else {
-            false
-        }
-    }) {
-        return iface.clone();
+/// Returns whether the IP address is one of the addresses assigned to the iface.
+fn has_ip_addr(iface: &Iface, ip_addr: &IpAddress) -> bool {
+    match ip_addr {
+        IpAddress::Ipv4(ipv4_addr) => iface
+            .ipv4_cidrs()
+            .iter()
+            .any(|ipv4_cidr| ipv4_cidr.address() == *ipv4_addr),
+        IpAddress::Ipv6(ipv6_addr) => iface
+            .ipv6_cidrs()
+            .iter()
+            .any(|ipv6_cidr| ipv6_cidr.address() == *ipv6_addr),
     }
-    // FIXME: use the virtio-net as the default interface
-    ifaces[0].clone()
 }
 
+/// The ports below this number are privileged, which can only be bound with
+/// `CAP_NET_BIND_SERVICE`.
+const PROT_SOCK: u16 = 1024;
+
 pub(super) fn bind_port(endpoint: &IpEndpoint, can_reuse: bool) -> Result<BoundPort> {
+    if endpoint.port != 0 && endpoint.port < PROT_SOCK && !can_bind_privileged_port() {
+        return_errno_with_message!(
+            Errno::EACCES,
+            "binding to a privileged port requires CAP_NET_BIND_SERVICE"
+        );
+    }
+
     let iface = match get_iface_to_bind(&endpoint.addr) {
         Some(iface) => iface,
         None => {
@@ -56,9 +61,21 @@ pub(super) fn bind_port(endpoint: &IpEndpoint, can_reuse: bool) -> Result<BoundP
         }
     };
 
-    let bind_port_config = BindPortConfig::new(endpoint.port, can_reuse);
+    // A new ephemeral port is never shared, so the reuse options make no sense for it.
+    let bind_port_config = BindPortConfig::new(endpoint.port, can_reuse && endpoint.port != 0);
 
-    Ok(iface.bind(bind_port_config)?)
+    Ok(iface.bind(endpoint.addr.version(), bind_port_config)?)
+}
+
+fn can_bind_privileged_port() -> bool {
+    let current = current_thread!();
+    let Some(posix_thread) = current.as_posix_thread() else {
+        return true;
+    };
+    posix_thread
+        .credentials()
+        .effective_capset()
+        .contains(CapSet::NET_BIND_SERVICE)
 }
 
 impl From<BindError> for Error {
@@ -74,8 +91,12 @@ impl From<BindError> for Error {
     }
 }
 
-pub(super) fn get_ephemeral_endpoint(remote_endpoint: &IpEndpoint) -> IpEndpoint {
-    let iface = get_ephemeral_iface(&remote_endpoint.addr);
-    let ip_addr = iface.ipv4_addr().unwrap();
-    IpEndpoint::new(IpAddress::Ipv4(ip_addr), 0)
+/// Gets a suitable local endpoint to deal with sendto/connect requests if the socket is not bound.
+///
+/// The iface and the local address are selected by looking up the route to the remote address.
+pub(super) fn get_ephemeral_endpoint(remote_endpoint: &IpEndpoint) -> Result<IpEndpoint> {
+    let Some((_, ip_addr)) = lookup_route(&remote_endpoint.addr) else {
+        return_errno_with_message!(Errno::ENETUNREACH, "the remote address is unreachable");
+    };
+    Ok(IpEndpoint::new(ip_addr, 0))
 }
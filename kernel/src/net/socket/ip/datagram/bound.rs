@@ -44,6 +44,14 @@ impl BoundDatagram {
         self.bound_socket.iface()
     }
 
+    pub fn set_multicast_ttl(&self, ttl: u8) {
+        self.bound_socket.set_multicast_hop_limit(ttl);
+    }
+
+    pub fn set_multicast_loop(&self, is_loop: bool) {
+        self.bound_socket.set_multicast_loop(is_loop);
+    }
+
     pub fn try_recv(
         &self,
         writer: &mut dyn MultiWrite,
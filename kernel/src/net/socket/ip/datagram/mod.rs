@@ -2,21 +2,22 @@
 
 use core::sync::atomic::{AtomicBool, Ordering};
 
-use aster_bigtcp::wire::IpEndpoint;
+use aster_bigtcp::wire::{IpEndpoint, IpVersion};
 use ostd::sync::PreemptDisabled;
 use takeable::Takeable;
 
-use self::{bound::BoundDatagram, unbound::UnboundDatagram};
-use super::{common::get_ephemeral_endpoint, UNSPECIFIED_LOCAL_ENDPOINT};
+use self::{bound::BoundDatagram, multicast::MulticastOptions, unbound::UnboundDatagram};
+use super::{common::get_ephemeral_endpoint, to_endpoint, unspecified_local_endpoint};
 use crate::{
     events::IoEvents,
     fs::{
         file_handle::FileLike,
         utils::{InodeMode, Metadata, StatusFlags},
     },
-    match_sock_option_mut,
+    match_sock_option_mut, match_sock_option_ref,
     net::socket::{
-        options::{Error as SocketError, SocketOption},
+        ip::options::{AddMembership, DropMembership, MulticastIf, MulticastLoop, MulticastTtl},
+        options::{Error as SocketError, SocketOption, ZeroCopy},
         util::{
             options::{SetSocketLevelOption, SocketOptionSet},
             send_recv_flags::SendRecvFlags,
@@ -31,25 +32,28 @@ use crate::{
 };
 
 mod bound;
+mod multicast;
 mod observer;
 mod unbound;
 
 pub(in crate::net) use self::observer::DatagramObserver;
 
-#[derive(Debug, Clone)]
 struct OptionSet {
     socket: SocketOptionSet,
+    multicast: MulticastOptions,
     // TODO: UDP option set
 }
 
 impl OptionSet {
     fn new() -> Self {
         let socket = SocketOptionSet::new_udp();
-        OptionSet { socket }
+        let multicast = MulticastOptions::new();
+        OptionSet { socket, multicast }
     }
 }
 
 pub struct DatagramSocket {
+    ip_version: IpVersion,
     options: RwLock<OptionSet>,
     inner: RwLock<Takeable<Inner>, PreemptDisabled>,
     is_nonblocking: AtomicBool,
@@ -65,7 +69,7 @@ impl Inner {
     fn bind(
         self,
         endpoint: &IpEndpoint,
-        can_reuse: bool,
+        options: &OptionSet,
         observer: DatagramObserver,
     ) -> core::result::Result<BoundDatagram, (Error, Self)> {
         let unbound_datagram = match self {
@@ -78,31 +82,43 @@ impl Inner {
             }
         };
 
+        let can_reuse = options.socket.reuse_addr() || options.socket.reuse_port();
         let bound_datagram = match unbound_datagram.bind(endpoint, can_reuse, observer) {
             Ok(bound_datagram) => bound_datagram,
             Err((err, unbound_datagram)) => return Err((err, Inner::Unbound(unbound_datagram))),
         };
+        bound_datagram.set_multicast_ttl(options.multicast.ttl());
+        bound_datagram.set_multicast_loop(options.multicast.is_loop());
         Ok(bound_datagram)
     }
 
     fn bind_to_ephemeral_endpoint(
         self,
         remote_endpoint: &IpEndpoint,
+        options: &OptionSet,
         observer: DatagramObserver,
     ) -> core::result::Result<BoundDatagram, (Error, Self)> {
         if let Inner::Bound(bound_datagram) = self {
             return Ok(bound_datagram);
         }
 
-        let endpoint = get_ephemeral_endpoint(remote_endpoint);
-        self.bind(&endpoint, false, observer)
+        let endpoint = match options.multicast.ephemeral_endpoint(remote_endpoint) {
+            Some(endpoint) => Ok(endpoint),
+            None => get_ephemeral_endpoint(remote_endpoint),
+        };
+        let endpoint = match endpoint {
+            Ok(endpoint) => endpoint,
+            Err(err) => return Err((err, self)),
+        };
+        self.bind(&endpoint, options, observer)
     }
 }
 
 impl DatagramSocket {
-    pub fn new(is_nonblocking: bool) -> Arc<Self> {
+    pub fn new(ip_version: IpVersion, is_nonblocking: bool) -> Arc<Self> {
         let unbound_datagram = UnboundDatagram::new();
         Arc::new(Self {
+            ip_version,
             inner: RwLock::new(Takeable::new(Inner::Unbound(unbound_datagram))),
             is_nonblocking: AtomicBool::new(is_nonblocking),
             pollee: Pollee::new(),
@@ -134,10 +150,12 @@ impl DatagramSocket {
         }
 
         // Slow path
+        let options = self.options.read();
         let mut inner = self.inner.write();
         inner.borrow_result(|owned_inner| {
             let bound_datagram = match owned_inner.bind_to_ephemeral_endpoint(
                 remote_endpoint,
+                &options,
                 DatagramObserver::new(self.pollee.clone()),
             ) {
                 Ok(bound_datagram) => bound_datagram,
@@ -210,6 +228,16 @@ impl DatagramSocket {
             Inner::Bound(bound_socket) => bound_socket.check_io_events(),
         }
     }
+
+    fn check_ip_version(&self, ip_version: IpVersion) -> Result<()> {
+        if self.ip_version != ip_version {
+            return_errno_with_message!(
+                Errno::ENOPROTOOPT,
+                "the option is not supported by the IP version"
+            );
+        }
+        Ok(())
+    }
 }
 
 impl Pollable for DatagramSocket {
@@ -277,14 +305,14 @@ impl FileLike for DatagramSocket {
 
 impl Socket for DatagramSocket {
     fn bind(&self, socket_addr: SocketAddr) -> Result<()> {
-        let endpoint = socket_addr.try_into()?;
+        let endpoint = to_endpoint(socket_addr, self.ip_version)?;
 
-        let can_reuse = self.options.read().socket.reuse_addr();
+        let options = self.options.read();
         let mut inner = self.inner.write();
         inner.borrow_result(|owned_inner| {
             let bound_datagram = match owned_inner.bind(
                 &endpoint,
-                can_reuse,
+                &options,
                 DatagramObserver::new(self.pollee.clone()),
             ) {
                 Ok(bound_datagram) => bound_datagram,
@@ -297,7 +325,7 @@ impl Socket for DatagramSocket {
     }
 
     fn connect(&self, socket_addr: SocketAddr) -> Result<()> {
-        let endpoint = socket_addr.try_into()?;
+        let endpoint = to_endpoint(socket_addr, self.ip_version)?;
 
         self.try_bind_ephemeral(&endpoint)?;
 
@@ -313,7 +341,7 @@ impl Socket for DatagramSocket {
     fn addr(&self) -> Result<SocketAddr> {
         let inner = self.inner.read();
         match inner.as_ref() {
-            Inner::Unbound(_) => Ok(UNSPECIFIED_LOCAL_ENDPOINT.into()),
+            Inner::Unbound(_) => Ok(unspecified_local_endpoint(self.ip_version).into()),
             Inner::Bound(bound_datagram) => Ok(bound_datagram.local_endpoint().into()),
         }
     }
@@ -337,12 +365,12 @@ impl Socket for DatagramSocket {
 
         let MessageHeader {
             addr,
-            control_message,
+            control_messages,
         } = message_header;
 
         let remote_endpoint = match addr {
             Some(remote_addr) => {
-                let endpoint = remote_addr.try_into()?;
+                let endpoint = to_endpoint(remote_addr, self.ip_version)?;
                 self.try_bind_ephemeral(&endpoint)?;
                 endpoint
             }
@@ -354,7 +382,7 @@ impl Socket for DatagramSocket {
             })?,
         };
 
-        if control_message.is_some() {
+        if !control_messages.is_empty() {
             // TODO: Support sending control message
             warn!("sending control message is not supported");
         }
@@ -373,11 +401,16 @@ impl Socket for DatagramSocket {
             warn!("unsupported flags: {:?}", flags);
         }
 
+        if flags.contains(SendRecvFlags::MSG_ERRQUEUE) {
+            // No extended errors are queued, since zero-copy transmission is not supported.
+            return_errno_with_message!(Errno::EAGAIN, "the error queue is empty");
+        }
+
         let (received_bytes, peer_addr) = self.recv(writer, flags)?;
 
         // TODO: Receive control message
 
-        let message_header = MessageHeader::new(Some(peer_addr), None);
+        let message_header = MessageHeader::new(Some(peer_addr), Vec::new());
 
         Ok((received_bytes, message_header))
     }
@@ -388,6 +421,25 @@ impl Socket for DatagramSocket {
                 self.options.write().socket.get_and_clear_sock_errors(socket_errors);
                 return Ok(());
             },
+            socket_zerocopy: ZeroCopy => {
+                socket_zerocopy.set(false);
+                return Ok(());
+            },
+            ip_multicast_if: MulticastIf => {
+                self.check_ip_version(IpVersion::Ipv4)?;
+                ip_multicast_if.set(self.options.read().multicast.iface());
+                return Ok(());
+            },
+            ip_multicast_ttl: MulticastTtl => {
+                self.check_ip_version(IpVersion::Ipv4)?;
+                ip_multicast_ttl.set(self.options.read().multicast.ttl() as u32);
+                return Ok(());
+            },
+            ip_multicast_loop: MulticastLoop => {
+                self.check_ip_version(IpVersion::Ipv4)?;
+                ip_multicast_loop.set(self.options.read().multicast.is_loop());
+                return Ok(());
+            },
             _ => ()
         });
 
@@ -395,6 +447,53 @@ impl Socket for DatagramSocket {
     }
 
     fn set_option(&self, option: &dyn SocketOption) -> Result<()> {
+        match_sock_option_ref!(option, {
+            _socket_zerocopy: ZeroCopy => {
+                // TODO: Pin the user pages and hand them to the device driver directly. Until
+                // then, `MSG_ZEROCOPY` is ignored, as Linux does when `SO_ZEROCOPY` is not set.
+                return_errno_with_message!(
+                    Errno::EOPNOTSUPP,
+                    "zero-copy transmission is not supported"
+                );
+            },
+            ip_multicast_if: MulticastIf => {
+                self.check_ip_version(IpVersion::Ipv4)?;
+                self.options.write().multicast.set_iface(*ip_multicast_if.get().unwrap())?;
+                return Ok(());
+            },
+            ip_multicast_ttl: MulticastTtl => {
+                self.check_ip_version(IpVersion::Ipv4)?;
+                let mut options = self.options.write();
+                options.multicast.set_ttl(*ip_multicast_ttl.get().unwrap())?;
+                if let Inner::Bound(bound_datagram) = self.inner.read().as_ref() {
+                    bound_datagram.set_multicast_ttl(options.multicast.ttl());
+                }
+                return Ok(());
+            },
+            ip_multicast_loop: MulticastLoop => {
+                self.check_ip_version(IpVersion::Ipv4)?;
+                let mut options = self.options.write();
+                options.multicast.set_loop(*ip_multicast_loop.get().unwrap());
+                if let Inner::Bound(bound_datagram) = self.inner.read().as_ref() {
+                    bound_datagram.set_multicast_loop(options.multicast.is_loop());
+                }
+                return Ok(());
+            },
+            ip_add_membership: AddMembership => {
+                self.check_ip_version(IpVersion::Ipv4)?;
+                let iface = self.options.write().multicast.join(ip_add_membership.get().unwrap())?;
+                // Send the membership report as soon as possible.
+                iface.poll();
+                return Ok(());
+            },
+            ip_drop_membership: DropMembership => {
+                self.check_ip_version(IpVersion::Ipv4)?;
+                self.options.write().multicast.leave(ip_drop_membership.get().unwrap())?;
+                return Ok(());
+            },
+            _ => ()
+        });
+
         let mut options = self.options.write();
         let mut inner = self.inner.write();
 
@@ -0,0 +1,194 @@
+// SPDX-License-Identifier: MPL-2.0
+
+use aster_bigtcp::wire::{IpAddress, IpEndpoint, Ipv4Address};
+
+use super::super::{
+    common::get_iface_to_bind,
+    options::{IpMreqn, IpMulticastIface},
+};
+use crate::{
+    net::iface::{get_iface_by_index, lookup_route, Iface},
+    prelude::*,
+};
+
+/// The default TTL of the multicast packets.
+///
+/// Like Linux, the multicast packets are not forwarded beyond the local network by default.
+const DEFAULT_MULTICAST_TTL: u8 = 1;
+
+/// The maximum number of the multicast groups that a socket can join.
+///
+/// This follows the default value of `net.ipv4.igmp_max_memberships` in Linux.
+const MAX_MEMBERSHIPS: usize = 20;
+
+/// The IPv4 multicast options of a datagram socket.
+pub(super) struct MulticastOptions {
+    ttl: u8,
+    is_loop: bool,
+    iface: IpMulticastIface,
+    memberships: Vec<Membership>,
+}
+
+/// A multicast group joined by a socket.
+///
+/// The iface leaves the group when the membership is dropped.
+struct Membership {
+    iface: Arc<Iface>,
+    group: Ipv4Address,
+}
+
+impl Drop for Membership {
+    fn drop(&mut self) {
+        self.iface.leave_ipv4_multicast_group(self.group);
+        // Send the leave message as soon as possible.
+        self.iface.poll();
+    }
+}
+
+impl MulticastOptions {
+    pub(super) fn new() -> Self {
+        Self {
+            ttl: DEFAULT_MULTICAST_TTL,
+            is_loop: true,
+            iface: IpMulticastIface {
+                addr: Ipv4Address::UNSPECIFIED,
+                ifindex: 0,
+            },
+            memberships: Vec::new(),
+        }
+    }
+
+    pub(super) fn ttl(&self) -> u8 {
+        self.ttl
+    }
+
+    pub(super) fn set_ttl(&mut self, ttl: u32) -> Result<()> {
+        // Like Linux, -1 restores the default value.
+        self.ttl = match ttl {
+            u32::MAX => DEFAULT_MULTICAST_TTL,
+            ttl @ 0..=255 => ttl as u8,
+            _ => return_errno_with_message!(Errno::EINVAL, "the multicast TTL is invalid"),
+        };
+        Ok(())
+    }
+
+    pub(super) fn is_loop(&self) -> bool {
+        self.is_loop
+    }
+
+    pub(super) fn set_loop(&mut self, is_loop: bool) {
+        self.is_loop = is_loop;
+    }
+
+    pub(super) fn iface(&self) -> IpMulticastIface {
+        self.iface
+    }
+
+    pub(super) fn set_iface(&mut self, iface: IpMulticastIface) -> Result<()> {
+        if iface.ifindex != 0 || !iface.addr.is_unspecified() {
+            find_iface(iface.addr, iface.ifindex).ok_or_else(|| {
+                Error::with_message(Errno::EADDRNOTAVAIL, "the iface does not exist")
+            })?;
+        }
+
+        self.iface = iface;
+        Ok(())
+    }
+
+    /// Returns the iface that sends the multicast packets, if it is specified by the user.
+    fn selected_iface(&self) -> Option<Arc<Iface>> {
+        if self.iface.ifindex == 0 && self.iface.addr.is_unspecified() {
+            return None;
+        }
+
+        find_iface(self.iface.addr, self.iface.ifindex)
+    }
+
+    /// Returns the local endpoint to send packets to the multicast group if the socket is not
+    /// bound.
+    ///
+    /// If the iface is not specified by the user, this returns `None` and the endpoint should be
+    /// selected by looking up the route.
+    pub(super) fn ephemeral_endpoint(&self, remote_endpoint: &IpEndpoint) -> Option<IpEndpoint> {
+        if !remote_endpoint.addr.is_multicast() {
+            return None;
+        }
+
+        let ipv4_addr = self.selected_iface()?.ipv4_addr()?;
+        Some(IpEndpoint::new(IpAddress::Ipv4(ipv4_addr), 0))
+    }
+
+    /// Joins the multicast group and returns the iface that should be polled.
+    pub(super) fn join(&mut self, request: &IpMreqn) -> Result<Arc<Iface>> {
+        let iface = find_membership_iface(request)?;
+
+        if self
+            .memberships
+            .iter()
+            .any(|membership| membership.is_same(&iface, request.multiaddr))
+        {
+            return_errno_with_message!(Errno::EADDRINUSE, "the multicast group is already joined");
+        }
+        if self.memberships.len() >= MAX_MEMBERSHIPS {
+            return_errno_with_message!(Errno::ENOBUFS, "too many multicast groups are joined");
+        }
+
+        iface.join_ipv4_multicast_group(request.multiaddr);
+        self.memberships.push(Membership {
+            iface: iface.clone(),
+            group: request.multiaddr,
+        });
+
+        Ok(iface)
+    }
+
+    /// Leaves the multicast group.
+    pub(super) fn leave(&mut self, request: &IpMreqn) -> Result<()> {
+        let iface = find_membership_iface(request)?;
+
+        let Some(index) = self
+            .memberships
+            .iter()
+            .position(|membership| membership.is_same(&iface, request.multiaddr))
+        else {
+            return_errno_with_message!(Errno::EADDRNOTAVAIL, "the multicast group is not joined");
+        };
+
+        self.memberships.swap_remove(index);
+
+        Ok(())
+    }
+}
+
+impl Membership {
+    fn is_same(&self, iface: &Arc<Iface>, group: Ipv4Address) -> bool {
+        Arc::ptr_eq(&self.iface, iface) && self.group == group
+    }
+}
+
+/// Finds the iface to join or leave the multicast group.
+///
+/// If neither the iface index nor the local address is specified, the iface is selected by
+/// looking up the route to the multicast group, like Linux.
+fn find_membership_iface(request: &IpMreqn) -> Result<Arc<Iface>> {
+    if !request.multiaddr.is_multicast() {
+        return_errno_with_message!(Errno::EINVAL, "the address is not a multicast address");
+    }
+
+    let iface = if request.ifindex == 0 && request.address.is_unspecified() {
+        lookup_route(&IpAddress::Ipv4(request.multiaddr)).map(|(iface, _)| iface)
+    } else {
+        find_iface(request.address, request.ifindex)
+    };
+
+    iface.ok_or_else(|| Error::with_message(Errno::ENODEV, "the iface does not exist"))
+}
+
+/// Finds the iface by its index, or by its local address if the index is zero.
+fn find_iface(addr: Ipv4Address, ifindex: u32) -> Option<Arc<Iface>> {
+    if ifindex != 0 {
+        get_iface_by_index(ifindex)
+    } else {
+        get_iface_to_bind(&IpAddress::Ipv4(addr))
+    }
+}
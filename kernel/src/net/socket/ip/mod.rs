@@ -3,6 +3,8 @@
 mod addr;
 mod common;
 pub mod datagram;
+pub mod options;
+pub mod raw;
 pub mod stream;
 
-use addr::UNSPECIFIED_LOCAL_ENDPOINT;
+use addr::{to_endpoint, unspecified_local_endpoint};
@@ -0,0 +1,40 @@
+// SPDX-License-Identifier: MPL-2.0
+
+use aster_bigtcp::wire::Ipv4Address;
+
+use crate::impl_socket_options;
+
+impl_socket_options!(
+    pub struct Ttl(u32);
+    pub struct HdrIncl(bool);
+    pub struct RecvTtl(bool);
+    pub struct UnicastHops(u32);
+    pub struct RecvHopLimit(bool);
+    pub struct MulticastIf(IpMulticastIface);
+    pub struct MulticastTtl(u32);
+    pub struct MulticastLoop(bool);
+    pub struct AddMembership(IpMreqn);
+    pub struct DropMembership(IpMreqn);
+);
+
+/// A request to join or leave an IPv4 multicast group.
+#[derive(Debug, Clone, Copy)]
+pub struct IpMreqn {
+    /// The multicast group.
+    pub multiaddr: Ipv4Address,
+    /// The local address of the iface, which is used if `ifindex` is zero.
+    pub address: Ipv4Address,
+    /// The index of the iface.
+    pub ifindex: u32,
+}
+
+/// The iface that sends the IPv4 multicast packets.
+///
+/// If `ifindex` is zero and `addr` is unspecified, the iface is selected by the routing table.
+#[derive(Debug, Clone, Copy)]
+pub struct IpMulticastIface {
+    /// The local address of the iface, which is used if `ifindex` is zero.
+    pub addr: Ipv4Address,
+    /// The index of the iface.
+    pub ifindex: u32,
+}
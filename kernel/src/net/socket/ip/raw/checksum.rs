@@ -0,0 +1,48 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! The Internet checksum.
+//!
+//! See <https://datatracker.ietf.org/doc/html/rfc1071>.
+
+use aster_bigtcp::wire::Ipv6Address;
+
+/// Computes the checksum of the data.
+pub(super) fn checksum(data: &[u8]) -> u16 {
+    fold(sum(data))
+}
+
+/// Computes the checksum of an upper-layer packet carried by IPv6, including the pseudo-header.
+///
+/// See <https://datatracker.ietf.org/doc/html/rfc8200#section-8.1>.
+pub(super) fn ipv6_checksum(
+    src_addr: &Ipv6Address,
+    dst_addr: &Ipv6Address,
+    next_header: u8,
+    data: &[u8],
+) -> u16 {
+    let pseudo_header_sum = sum(&src_addr.octets())
+        + sum(&dst_addr.octets())
+        + sum(&(data.len() as u32).to_be_bytes())
+        + next_header as u32;
+    fold(pseudo_header_sum + sum(data))
+}
+
+fn sum(data: &[u8]) -> u32 {
+    let mut chunks = data.chunks_exact(2);
+    let mut sum = chunks
+        .by_ref()
+        .map(|chunk| u16::from_be_bytes([chunk[0], chunk[1]]) as u32)
+        .fold(0u32, u32::wrapping_add);
+    if let [last] = chunks.remainder() {
+        sum = sum.wrapping_add((*last as u32) << 8);
+    }
+    // Fold the carries so that the sums can be added without overflows.
+    (sum & 0xffff) + (sum >> 16)
+}
+
+fn fold(mut sum: u32) -> u16 {
+    while sum > 0xffff {
+        sum = (sum & 0xffff) + (sum >> 16);
+    }
+    !(sum as u16)
+}
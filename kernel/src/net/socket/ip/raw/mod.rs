@@ -0,0 +1,45 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! Raw IP sockets and ping sockets.
+//!
+//! A `SOCK_RAW` socket sends and receives the IP packets of a specific protocol. An IPv4 raw
+//! socket receives the packets along with their IP headers, and it can also send packets with
+//! custom IP headers if `IP_HDRINCL` is set. An IPv6 raw socket sees only the payloads.
+//!
+//! A ping socket (`SOCK_DGRAM` with `IPPROTO_ICMP` or `IPPROTO_ICMPV6`) sends ICMP echo requests
+//! and receives the echo replies to them. The identifier of the echo requests is managed by the
+//! kernel like the port of a UDP socket, so no privilege is needed.
+//!
+//! The packets are captured by [`RawIpTap`], which is installed on all the ifaces.
+//!
+//! See <https://www.man7.org/linux/man-pages/man7/raw.7.html> and
+//! <https://www.man7.org/linux/man-pages/man7/icmp.7.html>.
+
+mod checksum;
+pub mod options;
+mod socket;
+mod tap;
+
+pub use socket::RawSocket;
+pub use tap::RawIpTap;
+
+/// The protocol number of ICMP.
+pub const IPPROTO_ICMP: u8 = 1;
+/// The protocol number of ICMPv6.
+pub const IPPROTO_ICMPV6: u8 = 58;
+/// The protocol number that indicates the IP header is provided by the user program.
+pub const IPPROTO_RAW: u8 = 255;
+
+/// The length of the IPv4 header without options.
+const IPV4_MIN_HEADER_LEN: usize = 20;
+/// The length of the IPv6 header.
+const IPV6_HEADER_LEN: usize = 40;
+/// The length of the header of ICMP echo messages.
+const ICMP_ECHO_HEADER_LEN: usize = 8;
+
+/// The ICMP message types of echo requests and echo replies.
+const ICMP_ECHO_REQUEST: u8 = 8;
+const ICMP_ECHO_REPLY: u8 = 0;
+/// The ICMPv6 message types of echo requests and echo replies.
+const ICMPV6_ECHO_REQUEST: u8 = 128;
+const ICMPV6_ECHO_REPLY: u8 = 129;
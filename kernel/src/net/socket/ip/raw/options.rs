@@ -0,0 +1,10 @@
+// SPDX-License-Identifier: MPL-2.0
+
+use crate::impl_socket_options;
+
+impl_socket_options!(
+    /// The ICMP types that are blocked, as a bitmap of the first 32 types.
+    pub struct IcmpFilter(u32);
+    /// The ICMPv6 types that are blocked, as a bitmap of all the 256 types.
+    pub struct Icmp6Filter([u32; 8]);
+);
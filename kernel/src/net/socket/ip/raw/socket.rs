@@ -0,0 +1,733 @@
+// SPDX-License-Identifier: MPL-2.0
+
+use alloc::collections::btree_set::BTreeSet;
+use core::sync::atomic::{AtomicBool, Ordering};
+
+use aster_bigtcp::{
+    errors::SendIpPacketError,
+    wire::{IpAddress, IpEndpoint, IpVersion, Ipv4Address, Ipv6Address},
+};
+
+use super::{
+    checksum::{checksum, ipv6_checksum},
+    options::{Icmp6Filter, IcmpFilter},
+    tap::RawReceiver,
+    ICMPV6_ECHO_REQUEST, ICMP_ECHO_HEADER_LEN, ICMP_ECHO_REQUEST, IPPROTO_ICMP, IPPROTO_ICMPV6,
+    IPPROTO_RAW, IPV4_MIN_HEADER_LEN, IPV6_HEADER_LEN,
+};
+use crate::{
+    events::IoEvents,
+    fs::{
+        file_handle::FileLike,
+        utils::{InodeMode, Metadata, StatusFlags},
+    },
+    match_sock_option_mut, match_sock_option_ref,
+    net::{
+        iface::lookup_route,
+        socket::{
+            ip::{
+                common::get_iface_to_bind,
+                options::{HdrIncl, RecvHopLimit, RecvTtl, Ttl, UnicastHops},
+                to_endpoint, unspecified_local_endpoint,
+            },
+            options::{Error as SocketError, SocketOption},
+            util::{
+                options::{SetSocketLevelOption, SocketOptionSet},
+                send_recv_flags::SendRecvFlags,
+                socket_addr::SocketAddr,
+                MessageHeader,
+            },
+            ControlMessage, Socket,
+        },
+    },
+    prelude::*,
+    process::signal::{PollHandle, Pollable},
+    util::{MultiRead, MultiWrite},
+};
+
+#[derive(Debug, Clone)]
+struct OptionSet {
+    socket: SocketOptionSet,
+    /// The TTL or the hop limit of the sent packets, where `None` means the default value.
+    hop_limit: Option<u8>,
+    /// Whether the IP header is provided by the user program.
+    hdr_incl: bool,
+    /// Whether the TTL or the hop limit of the received packets is reported.
+    recv_hop_limit: bool,
+}
+
+impl OptionSet {
+    fn new(protocol: u8) -> Self {
+        let socket = SocketOptionSet::new_raw();
+        OptionSet {
+            socket,
+            hop_limit: None,
+            // Like Linux, `IPPROTO_RAW` implies `IP_HDRINCL`.
+            hdr_incl: protocol == IPPROTO_RAW,
+            recv_hop_limit: false,
+        }
+    }
+}
+
+/// The default TTL or hop limit of the sent packets.
+///
+/// This is the same as the default value of `net.ipv4.ip_default_ttl` in Linux.
+const DEFAULT_HOP_LIMIT: u8 = 64;
+
+/// The maximum length of an IP packet.
+const MAX_IP_PACKET_LEN: usize = u16::MAX as usize;
+
+/// The identifiers that are used by the ping sockets.
+///
+/// Unlike Linux, IPv4 and IPv6 ping sockets share the identifiers.
+static PING_IDENTS: SpinLock<BTreeSet<u16>> = SpinLock::new(BTreeSet::new());
+
+/// The range of the identifiers that are allocated to the ping sockets which are not bound
+/// explicitly.
+///
+/// Like Linux, this is the same as the range of the ephemeral ports.
+const PING_IDENT_START: u16 = 32768;
+const PING_IDENT_END: u16 = 60999;
+
+/// A raw socket or a ping socket.
+pub struct RawSocket {
+    ip_version: IpVersion,
+    protocol: u8,
+    is_ping: bool,
+    options: RwLock<OptionSet>,
+    receiver: Arc<RawReceiver>,
+    is_nonblocking: AtomicBool,
+}
+
+impl RawSocket {
+    /// Creates a raw socket of the specified protocol.
+    pub fn new_raw(ip_version: IpVersion, protocol: u8, is_nonblocking: bool) -> Arc<Self> {
+        Self::new(ip_version, protocol, false, is_nonblocking)
+    }
+
+    /// Creates a ping socket.
+    pub fn new_ping(ip_version: IpVersion, is_nonblocking: bool) -> Arc<Self> {
+        let protocol = match ip_version {
+            IpVersion::Ipv4 => IPPROTO_ICMP,
+            IpVersion::Ipv6 => IPPROTO_ICMPV6,
+        };
+        Self::new(ip_version, protocol, true, is_nonblocking)
+    }
+
+    fn new(ip_version: IpVersion, protocol: u8, is_ping: bool, is_nonblocking: bool) -> Arc<Self> {
+        let options = OptionSet::new(protocol);
+        let receiver = RawReceiver::new_registered(
+            ip_version,
+            protocol,
+            is_ping,
+            options.socket.recv_buf() as usize,
+        );
+
+        Arc::new(Self {
+            ip_version,
+            protocol,
+            is_ping,
+            options: RwLock::new(options),
+            receiver,
+            is_nonblocking: AtomicBool::new(is_nonblocking),
+        })
+    }
+
+    pub fn is_nonblocking(&self) -> bool {
+        self.is_nonblocking.load(Ordering::Relaxed)
+    }
+
+    pub fn set_nonblocking(&self, is_nonblocking: bool) {
+        self.is_nonblocking.store(is_nonblocking, Ordering::Relaxed);
+    }
+
+    /// Binds the ping socket to the identifier, or to an unused identifier if `ident` is zero.
+    ///
+    /// This method returns the identifier that the socket is bound to.
+    fn bind_ident(&self, ident: u16) -> Result<u16> {
+        let mut state = self.receiver.state();
+        if state.ident().is_some() {
+            return_errno_with_message!(Errno::EINVAL, "the socket is already bound");
+        }
+
+        let mut ping_idents = PING_IDENTS.lock();
+        let ident = if ident != 0 {
+            if ping_idents.contains(&ident) {
+                return_errno_with_message!(Errno::EADDRINUSE, "the identifier is in use");
+            }
+            ident
+        } else {
+            let Some(ident) =
+                (PING_IDENT_START..=PING_IDENT_END).find(|ident| !ping_idents.contains(ident))
+            else {
+                return_errno_with_message!(Errno::EAGAIN, "no identifier is available");
+            };
+            ident
+        };
+        ping_idents.insert(ident);
+
+        state.set_ident(ident);
+        Ok(ident)
+    }
+
+    /// Returns the identifier of the ping socket, and binds the socket to an unused identifier
+    /// if it is not bound.
+    fn ident_or_bind_ephemeral(&self) -> Result<u16> {
+        if let Some(ident) = self.receiver.state().ident() {
+            return Ok(ident);
+        }
+
+        match self.bind_ident(0) {
+            Ok(ident) => Ok(ident),
+            // Another thread may bind the socket concurrently.
+            Err(err) => self.receiver.state().ident().ok_or(err),
+        }
+    }
+
+    fn try_send(&self, reader: &mut dyn MultiRead, remote_addr: &IpAddress) -> Result<usize> {
+        let len = reader.sum_lens();
+        if len > MAX_IP_PACKET_LEN {
+            return_errno_with_message!(Errno::EMSGSIZE, "the packet is too large");
+        }
+
+        let mut data = vec![0u8; len];
+        let read_len = reader.read(&mut VmWriter::from(data.as_mut_slice()))?;
+        data.truncate(read_len);
+
+        let ident = if self.is_ping {
+            Some(self.ident_or_bind_ephemeral()?)
+        } else {
+            None
+        };
+
+        let Some((iface, route_src_addr)) = lookup_route(remote_addr) else {
+            return_errno_with_message!(Errno::ENETUNREACH, "the remote address is unreachable");
+        };
+        let src_addr = self.receiver.state().local_addr().unwrap_or(route_src_addr);
+
+        let (hop_limit, hdr_incl) = {
+            let options = self.options.read();
+            (
+                options.hop_limit.unwrap_or(DEFAULT_HOP_LIMIT),
+                options.hdr_incl,
+            )
+        };
+
+        let packet = if let Some(ident) = ident {
+            fill_echo_request(&mut data, ident, &src_addr, remote_addr)?;
+            new_ip_packet(&src_addr, remote_addr, self.protocol, hop_limit, &data)?
+        } else if hdr_incl {
+            fill_ip_header(&mut data, &src_addr)?;
+            data
+        } else {
+            if let (IpAddress::Ipv6(src_addr), IpAddress::Ipv6(dst_addr)) = (src_addr, remote_addr)
+            {
+                // Like Linux, the checksum of ICMPv6 messages is always computed by the kernel.
+                if self.protocol == IPPROTO_ICMPV6 && data.len() >= 4 {
+                    data[2..4].fill(0);
+                    let checksum = ipv6_checksum(&src_addr, dst_addr, IPPROTO_ICMPV6, &data);
+                    data[2..4].copy_from_slice(&checksum.to_be_bytes());
+                }
+            }
+            new_ip_packet(&src_addr, remote_addr, self.protocol, hop_limit, &data)?
+        };
+
+        match iface.send_ip_packet(packet) {
+            Ok(()) => (),
+            Err(SendIpPacketError::Malformed) => {
+                return_errno_with_message!(Errno::EINVAL, "the IP header is invalid")
+            }
+            Err(SendIpPacketError::BufferFull) => {
+                return_errno_with_message!(Errno::ENOBUFS, "too many packets are being sent")
+            }
+        }
+        iface.poll();
+
+        Ok(read_len)
+    }
+
+    fn try_recv(
+        &self,
+        writer: &mut dyn MultiWrite,
+        flags: SendRecvFlags,
+    ) -> Result<(usize, IpAddress, u8)> {
+        let mut state = self.receiver.state();
+
+        let Some(packet) = state.front() else {
+            return_errno_with_message!(Errno::EAGAIN, "the receive queue is empty");
+        };
+
+        let copy_len = packet.data.len().min(writer.sum_lens());
+        writer.write(&mut VmReader::from(&packet.data[..copy_len]))?;
+
+        let len = if flags.contains(SendRecvFlags::MSG_TRUNC) {
+            packet.data.len()
+        } else {
+            copy_len
+        };
+        let src_addr = packet.src_addr;
+        let hop_limit = packet.hop_limit;
+
+        // Similar to other datagram sockets, the rest of the packet is discarded if the buffer is
+        // too small, unless `MSG_PEEK` is specified.
+        if !flags.contains(SendRecvFlags::MSG_PEEK) {
+            state.pop_front();
+            self.receiver.pollee().invalidate();
+        }
+
+        Ok((len, src_addr, hop_limit))
+    }
+
+    fn recv(
+        &self,
+        writer: &mut dyn MultiWrite,
+        flags: SendRecvFlags,
+    ) -> Result<(usize, IpAddress, u8)> {
+        if self.is_nonblocking() || flags.contains(SendRecvFlags::MSG_DONTWAIT) {
+            self.try_recv(writer, flags)
+        } else {
+            self.wait_events(IoEvents::IN, None, || self.try_recv(writer, flags))
+        }
+    }
+
+    fn check_io_events(&self) -> IoEvents {
+        let mut events = IoEvents::OUT;
+
+        if !self.receiver.state().is_empty() {
+            events |= IoEvents::IN;
+        }
+
+        events
+    }
+
+    /// Returns the port in the socket addresses.
+    ///
+    /// Like Linux, this is the identifier for ping sockets, or the protocol for raw sockets.
+    fn local_port(&self) -> u16 {
+        if self.is_ping {
+            self.receiver.state().ident().unwrap_or(0)
+        } else {
+            self.protocol as u16
+        }
+    }
+
+    fn check_ip_version(&self, ip_version: IpVersion) -> Result<()> {
+        if self.ip_version != ip_version {
+            return_errno_with_message!(
+                Errno::ENOPROTOOPT,
+                "the option is not supported by the IP version"
+            );
+        }
+        Ok(())
+    }
+
+    fn check_icmp_raw(&self, protocol: u8) -> Result<()> {
+        if self.is_ping || self.protocol != protocol {
+            return_errno_with_message!(
+                Errno::EOPNOTSUPP,
+                "the option is only supported by ICMP raw sockets"
+            );
+        }
+        Ok(())
+    }
+}
+
+impl Drop for RawSocket {
+    fn drop(&mut self) {
+        self.receiver.unregister();
+
+        if let Some(ident) = self.receiver.state().ident() {
+            PING_IDENTS.lock().remove(&ident);
+        }
+    }
+}
+
+impl Pollable for RawSocket {
+    fn poll(&self, mask: IoEvents, poller: Option<&mut PollHandle>) -> IoEvents {
+        self.receiver
+            .pollee()
+            .poll_with(mask, poller, || self.check_io_events())
+    }
+}
+
+impl FileLike for RawSocket {
+    fn read(&self, writer: &mut VmWriter) -> Result<usize> {
+        // TODO: set correct flags
+        let flags = SendRecvFlags::empty();
+        self.recv(writer, flags).map(|(len, _, _)| len)
+    }
+
+    fn write(&self, reader: &mut VmReader) -> Result<usize> {
+        let Some(remote_addr) = self.receiver.state().remote_addr() else {
+            return_errno_with_message!(
+                Errno::EDESTADDRREQ,
+                "the destination address is not specified"
+            );
+        };
+        self.try_send(reader, &remote_addr)
+    }
+
+    fn as_socket(&self) -> Option<&dyn Socket> {
+        Some(self)
+    }
+
+    fn status_flags(&self) -> StatusFlags {
+        // TODO: when we fully support O_ASYNC, return the flag
+        if self.is_nonblocking() {
+            StatusFlags::O_NONBLOCK
+        } else {
+            StatusFlags::empty()
+        }
+    }
+
+    fn set_status_flags(&self, new_flags: StatusFlags) -> Result<()> {
+        if new_flags.contains(StatusFlags::O_NONBLOCK) {
+            self.set_nonblocking(true);
+        } else {
+            self.set_nonblocking(false);
+        }
+        Ok(())
+    }
+
+    fn metadata(&self) -> Metadata {
+        // This is a dummy implementation.
+        // TODO: Add "SockFS" and link `RawSocket` to it.
+        Metadata::new_socket(
+            0,
+            InodeMode::from_bits_truncate(0o140777),
+            aster_block::BLOCK_SIZE,
+        )
+    }
+}
+
+impl Socket for RawSocket {
+    fn bind(&self, socket_addr: SocketAddr) -> Result<()> {
+        let endpoint = to_endpoint(socket_addr, self.ip_version)?;
+
+        let local_addr = if endpoint.addr.is_unspecified() {
+            None
+        } else if get_iface_to_bind(&endpoint.addr).is_some() {
+            Some(endpoint.addr)
+        } else {
+            return_errno_with_message!(
+                Errno::EADDRNOTAVAIL,
+                "the address is not available from the local machine"
+            );
+        };
+
+        if self.is_ping {
+            self.bind_ident(endpoint.port)?;
+        }
+        self.receiver.state().set_local_addr(local_addr);
+
+        Ok(())
+    }
+
+    fn connect(&self, socket_addr: SocketAddr) -> Result<()> {
+        let endpoint = to_endpoint(socket_addr, self.ip_version)?;
+
+        if self.is_ping {
+            self.ident_or_bind_ephemeral()?;
+        }
+        self.receiver.state().set_remote_addr(Some(endpoint.addr));
+
+        Ok(())
+    }
+
+    fn addr(&self) -> Result<SocketAddr> {
+        let local_addr = self.receiver.state().local_addr();
+        let addr = local_addr.unwrap_or(unspecified_local_endpoint(self.ip_version).addr);
+        Ok(IpEndpoint::new(addr, self.local_port()).into())
+    }
+
+    fn peer_addr(&self) -> Result<SocketAddr> {
+        self.receiver
+            .state()
+            .remote_addr()
+            .map(|addr| IpEndpoint::new(addr, 0).into())
+            .ok_or_else(|| Error::with_message(Errno::ENOTCONN, "the socket is not connected"))
+    }
+
+    fn sendmsg(
+        &self,
+        reader: &mut dyn MultiRead,
+        message_header: MessageHeader,
+        flags: SendRecvFlags,
+    ) -> Result<usize> {
+        // TODO: Deal with flags
+        if !flags.is_all_supported() {
+            warn!("unsupported flags: {:?}", flags);
+        }
+
+        let MessageHeader {
+            addr,
+            control_messages,
+        } = message_header;
+
+        let remote_addr = match addr {
+            Some(remote_addr) => to_endpoint(remote_addr, self.ip_version)?.addr,
+            None => self.receiver.state().remote_addr().ok_or_else(|| {
+                Error::with_message(
+                    Errno::EDESTADDRREQ,
+                    "the destination address is not specified",
+                )
+            })?,
+        };
+
+        if !control_messages.is_empty() {
+            // TODO: Support sending control message
+            warn!("sending control message is not supported");
+        }
+
+        self.try_send(reader, &remote_addr)
+    }
+
+    fn recvmsg(
+        &self,
+        writer: &mut dyn MultiWrite,
+        flags: SendRecvFlags,
+    ) -> Result<(usize, MessageHeader)> {
+        let supported_flags =
+            SendRecvFlags::MSG_PEEK | SendRecvFlags::MSG_TRUNC | SendRecvFlags::MSG_DONTWAIT;
+        if !supported_flags.contains(flags) {
+            warn!("unsupported flags: {:?}", flags - supported_flags);
+        }
+
+        let (received_len, src_addr, hop_limit) = self.recv(writer, flags)?;
+
+        let mut control_messages = Vec::new();
+        if self.options.read().recv_hop_limit {
+            control_messages.push(match self.ip_version {
+                IpVersion::Ipv4 => ControlMessage::Ttl(hop_limit),
+                IpVersion::Ipv6 => ControlMessage::HopLimit(hop_limit),
+            });
+        }
+
+        let message_header =
+            MessageHeader::new(Some(IpEndpoint::new(src_addr, 0).into()), control_messages);
+
+        Ok((received_len, message_header))
+    }
+
+    fn get_option(&self, option: &mut dyn SocketOption) -> Result<()> {
+        match_sock_option_mut!(option, {
+            socket_errors: SocketError => {
+                self.options.write().socket.get_and_clear_sock_errors(socket_errors);
+                return Ok(());
+            },
+            ip_ttl: Ttl => {
+                self.check_ip_version(IpVersion::Ipv4)?;
+                let hop_limit = self.options.read().hop_limit.unwrap_or(DEFAULT_HOP_LIMIT);
+                ip_ttl.set(hop_limit as u32);
+                return Ok(());
+            },
+            ip_hdr_incl: HdrIncl => {
+                self.check_ip_version(IpVersion::Ipv4)?;
+                ip_hdr_incl.set(self.options.read().hdr_incl);
+                return Ok(());
+            },
+            ip_recv_ttl: RecvTtl => {
+                self.check_ip_version(IpVersion::Ipv4)?;
+                ip_recv_ttl.set(self.options.read().recv_hop_limit);
+                return Ok(());
+            },
+            ipv6_unicast_hops: UnicastHops => {
+                self.check_ip_version(IpVersion::Ipv6)?;
+                let hop_limit = self.options.read().hop_limit.unwrap_or(DEFAULT_HOP_LIMIT);
+                ipv6_unicast_hops.set(hop_limit as u32);
+                return Ok(());
+            },
+            ipv6_recv_hop_limit: RecvHopLimit => {
+                self.check_ip_version(IpVersion::Ipv6)?;
+                ipv6_recv_hop_limit.set(self.options.read().recv_hop_limit);
+                return Ok(());
+            },
+            icmp_filter: IcmpFilter => {
+                self.check_icmp_raw(IPPROTO_ICMP)?;
+                icmp_filter.set(self.receiver.state().icmp_filter()[0]);
+                return Ok(());
+            },
+            icmp6_filter: Icmp6Filter => {
+                self.check_icmp_raw(IPPROTO_ICMPV6)?;
+                icmp6_filter.set(*self.receiver.state().icmp_filter());
+                return Ok(());
+            },
+            _ => ()
+        });
+
+        self.options.read().socket.get_option(option)
+    }
+
+    fn set_option(&self, option: &dyn SocketOption) -> Result<()> {
+        match_sock_option_ref!(option, {
+            ip_ttl: Ttl => {
+                self.check_ip_version(IpVersion::Ipv4)?;
+                // Like Linux, -1 restores the default value.
+                let hop_limit = match *ip_ttl.get().unwrap() {
+                    u32::MAX => None,
+                    ttl @ 1..=255 => Some(ttl as u8),
+                    _ => return_errno_with_message!(Errno::EINVAL, "the TTL is invalid"),
+                };
+                self.options.write().hop_limit = hop_limit;
+                return Ok(());
+            },
+            ip_hdr_incl: HdrIncl => {
+                self.check_ip_version(IpVersion::Ipv4)?;
+                if self.is_ping {
+                    return_errno_with_message!(
+                        Errno::ENOPROTOOPT,
+                        "the option is not supported by ping sockets"
+                    );
+                }
+                self.options.write().hdr_incl = *ip_hdr_incl.get().unwrap();
+                return Ok(());
+            },
+            ip_recv_ttl: RecvTtl => {
+                self.check_ip_version(IpVersion::Ipv4)?;
+                self.options.write().recv_hop_limit = *ip_recv_ttl.get().unwrap();
+                return Ok(());
+            },
+            ipv6_unicast_hops: UnicastHops => {
+                self.check_ip_version(IpVersion::Ipv6)?;
+                // Like Linux, -1 restores the default value.
+                let hop_limit = match *ipv6_unicast_hops.get().unwrap() {
+                    u32::MAX => None,
+                    hop_limit @ 0..=255 => Some(hop_limit as u8),
+                    _ => return_errno_with_message!(Errno::EINVAL, "the hop limit is invalid"),
+                };
+                self.options.write().hop_limit = hop_limit;
+                return Ok(());
+            },
+            ipv6_recv_hop_limit: RecvHopLimit => {
+                self.check_ip_version(IpVersion::Ipv6)?;
+                self.options.write().recv_hop_limit = *ipv6_recv_hop_limit.get().unwrap();
+                return Ok(());
+            },
+            icmp_filter: IcmpFilter => {
+                self.check_icmp_raw(IPPROTO_ICMP)?;
+                let mut filter = [0; 8];
+                filter[0] = *icmp_filter.get().unwrap();
+                self.receiver.state().set_icmp_filter(filter);
+                return Ok(());
+            },
+            icmp6_filter: Icmp6Filter => {
+                self.check_icmp_raw(IPPROTO_ICMPV6)?;
+                self.receiver.state().set_icmp_filter(*icmp6_filter.get().unwrap());
+                return Ok(());
+            },
+            _ => ()
+        });
+
+        let mut options = self.options.write();
+        options.socket.set_option(option, &mut RawSocketOption)?;
+
+        let recv_buf = options.socket.recv_buf() as usize;
+        self.receiver.state().set_recv_buf(recv_buf);
+
+        Ok(())
+    }
+}
+
+/// Fills the identifier and the checksum of an ICMP or ICMPv6 echo request.
+fn fill_echo_request(
+    data: &mut [u8],
+    ident: u16,
+    src_addr: &IpAddress,
+    dst_addr: &IpAddress,
+) -> Result<()> {
+    let echo_request = match dst_addr {
+        IpAddress::Ipv4(_) => ICMP_ECHO_REQUEST,
+        IpAddress::Ipv6(_) => ICMPV6_ECHO_REQUEST,
+    };
+    // Like Linux, ping sockets can only send echo requests.
+    if data.len() < ICMP_ECHO_HEADER_LEN || data[0] != echo_request || data[1] != 0 {
+        return_errno_with_message!(Errno::EINVAL, "the message is not an echo request");
+    }
+
+    data[2..4].fill(0);
+    data[4..6].copy_from_slice(&ident.to_be_bytes());
+
+    let checksum = match (src_addr, dst_addr) {
+        (IpAddress::Ipv6(src_addr), IpAddress::Ipv6(dst_addr)) => {
+            ipv6_checksum(src_addr, dst_addr, IPPROTO_ICMPV6, data)
+        }
+        _ => checksum(data),
+    };
+    data[2..4].copy_from_slice(&checksum.to_be_bytes());
+
+    Ok(())
+}
+
+/// Creates an IP packet that carries the payload.
+fn new_ip_packet(
+    src_addr: &IpAddress,
+    dst_addr: &IpAddress,
+    protocol: u8,
+    hop_limit: u8,
+    payload: &[u8],
+) -> Result<Vec<u8>> {
+    let header_len = match dst_addr {
+        IpAddress::Ipv4(_) => IPV4_MIN_HEADER_LEN,
+        IpAddress::Ipv6(_) => IPV6_HEADER_LEN,
+    };
+    if header_len + payload.len() > MAX_IP_PACKET_LEN {
+        return_errno_with_message!(Errno::EMSGSIZE, "the packet is too large");
+    }
+
+    let mut packet = vec![0u8; header_len + payload.len()];
+    match (src_addr, dst_addr) {
+        (IpAddress::Ipv4(src_addr), IpAddress::Ipv4(dst_addr)) => {
+            packet[0] = 0x45;
+            packet[2..4].copy_from_slice(&(packet.len() as u16).to_be_bytes());
+            packet[8] = hop_limit;
+            packet[9] = protocol;
+            packet[12..16].copy_from_slice(&src_addr.octets());
+            packet[16..20].copy_from_slice(&dst_addr.octets());
+        }
+        (IpAddress::Ipv6(src_addr), IpAddress::Ipv6(dst_addr)) => {
+            packet[0] = 0x60;
+            packet[4..6].copy_from_slice(&(payload.len() as u16).to_be_bytes());
+            packet[6] = protocol;
+            packet[7] = hop_limit;
+            packet[8..24].copy_from_slice(&src_addr.octets());
+            packet[24..40].copy_from_slice(&dst_addr.octets());
+        }
+        _ => unreachable!("the source address is selected by the destination address"),
+    }
+    packet[header_len..].copy_from_slice(payload);
+
+    Ok(packet)
+}
+
+/// Fills the length and the unspecified source address of an IP header provided by the user
+/// program.
+fn fill_ip_header(packet: &mut [u8], src_addr: &IpAddress) -> Result<()> {
+    match src_addr {
+        IpAddress::Ipv4(src_addr) => {
+            if packet.len() < IPV4_MIN_HEADER_LEN {
+                return_errno_with_message!(Errno::EINVAL, "the packet is too short");
+            }
+            let len = packet.len() as u16;
+            packet[2..4].copy_from_slice(&len.to_be_bytes());
+            if packet[12..16] == Ipv4Address::UNSPECIFIED.octets() {
+                packet[12..16].copy_from_slice(&src_addr.octets());
+            }
+        }
+        IpAddress::Ipv6(src_addr) => {
+            if packet.len() < IPV6_HEADER_LEN {
+                return_errno_with_message!(Errno::EINVAL, "the packet is too short");
+            }
+            let payload_len = (packet.len() - IPV6_HEADER_LEN) as u16;
+            packet[4..6].copy_from_slice(&payload_len.to_be_bytes());
+            if packet[8..24] == Ipv6Address::UNSPECIFIED.octets() {
+                packet[8..24].copy_from_slice(&src_addr.octets());
+            }
+        }
+    }
+
+    Ok(())
+}
+
+struct RawSocketOption;
+
+impl SetSocketLevelOption for RawSocketOption {}
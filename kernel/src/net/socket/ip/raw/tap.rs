@@ -0,0 +1,283 @@
+// SPDX-License-Identifier: MPL-2.0
+
+use alloc::collections::vec_deque::VecDeque;
+
+use aster_bigtcp::{
+    iface::TapIpPacket,
+    wire::{IpAddress, IpVersion, Ipv4Address, Ipv6Address},
+};
+use ostd::sync::LocalIrqDisabled;
+
+use super::{
+    ICMPV6_ECHO_REPLY, ICMP_ECHO_HEADER_LEN, ICMP_ECHO_REPLY, IPPROTO_ICMP, IPPROTO_ICMPV6,
+    IPV4_MIN_HEADER_LEN, IPV6_HEADER_LEN,
+};
+use crate::{events::IoEvents, prelude::*, process::signal::Pollee};
+
+/// The receivers of all the raw sockets and the ping sockets.
+///
+/// The lock disables local IRQs because packets are tapped while the ifaces are being polled,
+/// which may happen in the IRQ context.
+static RECEIVERS: SpinLock<Vec<Arc<RawReceiver>>, LocalIrqDisabled> = SpinLock::new(Vec::new());
+
+/// A tap that delivers the IP packets destined to the local host to the raw sockets and the ping
+/// sockets.
+pub struct RawIpTap;
+
+impl TapIpPacket for RawIpTap {
+    fn tap_ip_packet(&self, packet: &[u8]) {
+        let Some(header) = IpHeader::parse(packet) else {
+            return;
+        };
+
+        for receiver in RECEIVERS.lock().iter() {
+            receiver.receive(packet, &header);
+        }
+    }
+}
+
+/// The fields of an IP header that decide which sockets can receive the packet.
+struct IpHeader {
+    ip_version: IpVersion,
+    protocol: u8,
+    hop_limit: u8,
+    src_addr: IpAddress,
+    dst_addr: IpAddress,
+    header_len: usize,
+}
+
+impl IpHeader {
+    fn parse(packet: &[u8]) -> Option<Self> {
+        match packet.first()? >> 4 {
+            4 => {
+                let header_len = (packet[0] & 0xf) as usize * 4;
+                if header_len < IPV4_MIN_HEADER_LEN || packet.len() < header_len {
+                    return None;
+                }
+                let src_addr: [u8; 4] = packet[12..16].try_into().unwrap();
+                let dst_addr: [u8; 4] = packet[16..20].try_into().unwrap();
+                Some(Self {
+                    ip_version: IpVersion::Ipv4,
+                    protocol: packet[9],
+                    hop_limit: packet[8],
+                    src_addr: IpAddress::Ipv4(Ipv4Address::from(src_addr)),
+                    dst_addr: IpAddress::Ipv4(Ipv4Address::from(dst_addr)),
+                    header_len,
+                })
+            }
+            6 => {
+                // TODO: Skip the extension headers.
+                if packet.len() < IPV6_HEADER_LEN {
+                    return None;
+                }
+                let src_addr: [u8; 16] = packet[8..24].try_into().unwrap();
+                let dst_addr: [u8; 16] = packet[24..40].try_into().unwrap();
+                Some(Self {
+                    ip_version: IpVersion::Ipv6,
+                    protocol: packet[6],
+                    hop_limit: packet[7],
+                    src_addr: IpAddress::Ipv6(Ipv6Address::from(src_addr)),
+                    dst_addr: IpAddress::Ipv6(Ipv6Address::from(dst_addr)),
+                    header_len: IPV6_HEADER_LEN,
+                })
+            }
+            _ => None,
+        }
+    }
+}
+
+/// The receiving side of a raw socket or a ping socket.
+pub(super) struct RawReceiver {
+    ip_version: IpVersion,
+    protocol: u8,
+    is_ping: bool,
+    state: SpinLock<ReceiverState, LocalIrqDisabled>,
+    pollee: Pollee,
+}
+
+pub(super) struct ReceiverState {
+    /// The bound local address, where `None` means any address.
+    local_addr: Option<IpAddress>,
+    /// The connected remote address, where `None` means any address.
+    remote_addr: Option<IpAddress>,
+    /// The identifier of the echo requests, if this is a bound ping socket.
+    ident: Option<u16>,
+    /// The blocked ICMP or ICMPv6 types.
+    icmp_filter: [u32; 8],
+    recv_buf: usize,
+    queue: VecDeque<ReceivedPacket>,
+    queued_bytes: usize,
+}
+
+/// A packet that is received by a raw socket or a ping socket.
+pub(super) struct ReceivedPacket {
+    pub(super) data: Vec<u8>,
+    pub(super) src_addr: IpAddress,
+    /// The TTL or the hop limit in the IP header.
+    pub(super) hop_limit: u8,
+}
+
+impl RawReceiver {
+    /// Creates a receiver and registers it to receive packets from the ifaces.
+    pub(super) fn new_registered(
+        ip_version: IpVersion,
+        protocol: u8,
+        is_ping: bool,
+        recv_buf: usize,
+    ) -> Arc<Self> {
+        let state = ReceiverState {
+            local_addr: None,
+            remote_addr: None,
+            ident: None,
+            icmp_filter: [0; 8],
+            recv_buf,
+            queue: VecDeque::new(),
+            queued_bytes: 0,
+        };
+
+        let receiver = Arc::new(Self {
+            ip_version,
+            protocol,
+            is_ping,
+            state: SpinLock::new(state),
+            pollee: Pollee::new(),
+        });
+        RECEIVERS.lock().push(receiver.clone());
+
+        receiver
+    }
+
+    /// Unregisters the receiver so that it no longer receives packets.
+    pub(super) fn unregister(self: &Arc<Self>) {
+        RECEIVERS
+            .lock()
+            .retain(|receiver| !Arc::ptr_eq(receiver, self));
+    }
+
+    pub(super) fn state(&self) -> SpinLockGuard<'_, ReceiverState, LocalIrqDisabled> {
+        self.state.lock()
+    }
+
+    pub(super) fn pollee(&self) -> &Pollee {
+        &self.pollee
+    }
+
+    fn receive(&self, packet: &[u8], header: &IpHeader) {
+        if header.ip_version != self.ip_version || header.protocol != self.protocol {
+            return;
+        }
+
+        let mut state = self.state.lock();
+
+        if state
+            .local_addr
+            .is_some_and(|local_addr| local_addr != header.dst_addr)
+            || state
+                .remote_addr
+                .is_some_and(|remote_addr| remote_addr != header.src_addr)
+        {
+            return;
+        }
+
+        let payload = &packet[header.header_len..];
+        let data = if self.is_ping {
+            // A ping socket receives only the echo replies with its identifier.
+            let echo_reply = match self.ip_version {
+                IpVersion::Ipv4 => ICMP_ECHO_REPLY,
+                IpVersion::Ipv6 => ICMPV6_ECHO_REPLY,
+            };
+            if payload.len() < ICMP_ECHO_HEADER_LEN
+                || payload[0] != echo_reply
+                || state.ident != Some(u16::from_be_bytes([payload[4], payload[5]]))
+            {
+                return;
+            }
+            payload
+        } else {
+            if matches!(self.protocol, IPPROTO_ICMP | IPPROTO_ICMPV6)
+                && payload
+                    .first()
+                    .is_some_and(|icmp_type| state.is_icmp_type_blocked(*icmp_type))
+            {
+                return;
+            }
+            // Like Linux, IPv4 raw sockets receive the IP headers, but IPv6 raw sockets do not.
+            match self.ip_version {
+                IpVersion::Ipv4 => packet,
+                IpVersion::Ipv6 => payload,
+            }
+        };
+
+        // Like Linux, the packet is dropped only if the receive buffer is already full, so a
+        // packet can always be received if the queue is empty.
+        if state.queued_bytes >= state.recv_buf {
+            return;
+        }
+
+        state.queue.push_back(ReceivedPacket {
+            data: data.to_vec(),
+            src_addr: header.src_addr,
+            hop_limit: header.hop_limit,
+        });
+        state.queued_bytes += data.len();
+        drop(state);
+
+        self.pollee.notify(IoEvents::IN);
+    }
+}
+
+impl ReceiverState {
+    pub(super) fn local_addr(&self) -> Option<IpAddress> {
+        self.local_addr
+    }
+
+    pub(super) fn set_local_addr(&mut self, local_addr: Option<IpAddress>) {
+        self.local_addr = local_addr;
+    }
+
+    pub(super) fn remote_addr(&self) -> Option<IpAddress> {
+        self.remote_addr
+    }
+
+    pub(super) fn set_remote_addr(&mut self, remote_addr: Option<IpAddress>) {
+        self.remote_addr = remote_addr;
+    }
+
+    pub(super) fn ident(&self) -> Option<u16> {
+        self.ident
+    }
+
+    pub(super) fn set_ident(&mut self, ident: u16) {
+        self.ident = Some(ident);
+    }
+
+    pub(super) fn icmp_filter(&self) -> &[u32; 8] {
+        &self.icmp_filter
+    }
+
+    pub(super) fn set_icmp_filter(&mut self, icmp_filter: [u32; 8]) {
+        self.icmp_filter = icmp_filter;
+    }
+
+    fn is_icmp_type_blocked(&self, icmp_type: u8) -> bool {
+        self.icmp_filter[(icmp_type / 32) as usize] & (1 << (icmp_type % 32)) != 0
+    }
+
+    pub(super) fn set_recv_buf(&mut self, recv_buf: usize) {
+        self.recv_buf = recv_buf;
+    }
+
+    pub(super) fn front(&self) -> Option<&ReceivedPacket> {
+        self.queue.front()
+    }
+
+    pub(super) fn pop_front(&mut self) {
+        if let Some(packet) = self.queue.pop_front() {
+            self.queued_bytes -= packet.data.len();
+        }
+    }
+
+    pub(super) fn is_empty(&self) -> bool {
+        self.queue.is_empty()
+    }
+}
@@ -206,6 +206,11 @@ impl ConnectedStream {
         })
     }
 
+    /// Aborts the connection, sending a RST to the peer.
+    pub(super) fn abort(&self) {
+        self.tcp_conn.abort();
+    }
+
     pub(super) fn set_raw_option<R>(
         &self,
         set_option: impl FnOnce(&dyn RawTcpSetOption) -> R,
@@ -53,7 +53,10 @@ impl InitStream {
         self,
         remote_endpoint: &IpEndpoint,
     ) -> core::result::Result<BoundPort, (Error, Self)> {
-        let endpoint = get_ephemeral_endpoint(remote_endpoint);
+        let endpoint = match get_ephemeral_endpoint(remote_endpoint) {
+            Ok(endpoint) => endpoint,
+            Err(err) => return Err((err, self)),
+        };
         self.bind(&endpoint, false)
     }
 
@@ -75,6 +78,7 @@ impl InitStream {
     pub fn listen(
         self,
         backlog: usize,
+        reuse_port: bool,
         option: &RawTcpOption,
         observer: StreamObserver,
     ) -> core::result::Result<ListenStream, (Error, Self)> {
@@ -88,7 +92,7 @@ impl InitStream {
             ));
         };
 
-        match ListenStream::new(bound_port, backlog, option, observer) {
+        match ListenStream::new(bound_port, backlog, reuse_port, option, observer) {
             Ok(listen_stream) => Ok(listen_stream),
             Err((bound_port, error)) => Err((error, Self::Bound(bound_port))),
         }
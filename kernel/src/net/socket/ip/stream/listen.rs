@@ -21,13 +21,14 @@ impl ListenStream {
     pub fn new(
         bound_port: BoundPort,
         backlog: usize,
+        reuse_port: bool,
         option: &RawTcpOption,
         observer: StreamObserver,
     ) -> core::result::Result<Self, (BoundPort, Error)> {
         const SOMAXCONN: usize = 4096;
         let max_conn = SOMAXCONN.min(backlog);
 
-        match TcpListener::new_listen(bound_port, max_conn, option, observer) {
+        match TcpListener::new_listen(bound_port, max_conn, reuse_port, option, observer) {
             Ok(tcp_listener) => Ok(Self { tcp_listener }),
             Err((bound_port, ListenError::AddressInUse)) => Err((
                 bound_port,
@@ -3,19 +3,19 @@
 use core::sync::atomic::{AtomicBool, Ordering};
 
 use aster_bigtcp::{
-    socket::{NeedIfacePoll, RawTcpOption, RawTcpSetOption},
-    wire::IpEndpoint,
+    socket::{NeedIfacePoll, RawTcpCongestionControl, RawTcpOption, RawTcpSetOption},
+    wire::{IpEndpoint, IpVersion},
 };
 use connected::ConnectedStream;
 use connecting::{ConnResult, ConnectingStream};
 use init::InitStream;
 use listen::ListenStream;
-use options::{Congestion, KeepIdle, MaxSegment, NoDelay, WindowClamp, KEEPALIVE_INTERVAL};
+use options::{Congestion, KeepCount, KeepIdle, KeepInterval, MaxSegment, NoDelay, WindowClamp};
 use ostd::sync::{PreemptDisabled, RwLockReadGuard, RwLockWriteGuard};
 use takeable::Takeable;
 use util::TcpOptionSet;
 
-use super::UNSPECIFIED_LOCAL_ENDPOINT;
+use super::{to_endpoint, unspecified_local_endpoint};
 use crate::{
     events::IoEvents,
     fs::{
@@ -26,7 +26,7 @@ use crate::{
     net::{
         iface::Iface,
         socket::{
-            options::{Error as SocketError, SocketOption},
+            options::{Error as SocketError, SocketOption, ZeroCopy},
             util::{
                 options::{SetSocketLevelOption, SocketOptionSet},
                 send_recv_flags::SendRecvFlags,
@@ -39,7 +39,8 @@ use crate::{
     },
     prelude::*,
     process::signal::{PollHandle, Pollable, Pollee},
-    util::{MultiRead, MultiWrite},
+    security,
+    util::{net::Protocol, MultiRead, MultiWrite},
 };
 
 mod connected;
@@ -54,6 +55,7 @@ pub(in crate::net) use self::observer::StreamObserver;
 pub use self::util::CongestionControl;
 
 pub struct StreamSocket {
+    ip_version: IpVersion,
     options: RwLock<OptionSet>,
     state: RwLock<Takeable<State>, PreemptDisabled>,
     is_nonblocking: AtomicBool,
@@ -85,17 +87,22 @@ impl OptionSet {
     }
 
     fn raw(&self) -> RawTcpOption {
+        let (keep_alive, timeout) = self.tcp.raw_keep_alive(self.socket.keep_alive());
+
         RawTcpOption {
-            keep_alive: self.socket.keep_alive().then_some(KEEPALIVE_INTERVAL),
+            keep_alive,
+            timeout,
             is_nagle_enabled: !self.tcp.no_delay(),
+            congestion_control: self.tcp.congestion().into(),
         }
     }
 }
 
 impl StreamSocket {
-    pub fn new(is_nonblocking: bool) -> Arc<Self> {
+    pub fn new(ip_version: IpVersion, is_nonblocking: bool) -> Arc<Self> {
         let init_stream = InitStream::new();
         Arc::new(Self {
+            ip_version,
             options: RwLock::new(OptionSet::new()),
             state: RwLock::new(Takeable::new(State::Init(init_stream))),
             is_nonblocking: AtomicBool::new(is_nonblocking),
@@ -115,15 +122,25 @@ impl StreamSocket {
                 options.tcp.set_no_delay(true);
             }
 
+            if matches!(
+                raw_tcp_socket.congestion_control(),
+                RawTcpCongestionControl::Cubic
+            ) {
+                options.tcp.set_congestion(CongestionControl::Cubic);
+            }
+
             // TODO: Update other options for a newly-accepted socket
 
             options
         });
 
+        let ip_version = connected_stream.local_endpoint().addr.version();
+
         let pollee = Pollee::new();
         connected_stream.init_observer(StreamObserver::new(pollee.clone()));
 
         Arc::new(Self {
+            ip_version,
             options: RwLock::new(options),
             state: RwLock::new(Takeable::new(State::Connected(connected_stream))),
             is_nonblocking: AtomicBool::new(false),
@@ -470,9 +487,13 @@ impl FileLike for StreamSocket {
 
 impl Socket for StreamSocket {
     fn bind(&self, socket_addr: SocketAddr) -> Result<()> {
-        let endpoint = socket_addr.try_into()?;
+        let endpoint = to_endpoint(socket_addr, self.ip_version)?;
+        security::socket_bind(Protocol::IPPROTO_TCP, endpoint.port)?;
 
-        let can_reuse = self.options.read().socket.reuse_addr();
+        let can_reuse = {
+            let options = self.options.read();
+            options.socket.reuse_addr() || options.socket.reuse_port()
+        };
         let mut state = self.write_updated_state();
 
         state.borrow_result(|owned_state| {
@@ -498,7 +519,8 @@ impl Socket for StreamSocket {
     }
 
     fn connect(&self, socket_addr: SocketAddr) -> Result<()> {
-        let remote_endpoint = socket_addr.try_into()?;
+        let remote_endpoint = to_endpoint(socket_addr, self.ip_version)?;
+        security::socket_connect(Protocol::IPPROTO_TCP, remote_endpoint.port)?;
 
         if let Some(result) = self.start_connect(&remote_endpoint) {
             return result;
@@ -510,6 +532,7 @@ impl Socket for StreamSocket {
     fn listen(&self, backlog: usize) -> Result<()> {
         let (options, mut state) = self.update_connecting();
 
+        let reuse_port = options.socket.reuse_port();
         let raw_option = options.raw();
 
         state.borrow_result(|owned_state| {
@@ -531,6 +554,7 @@ impl Socket for StreamSocket {
 
             let listen_stream = match init_stream.listen(
                 backlog,
+                reuse_port,
                 &raw_option,
                 StreamObserver::new(self.pollee.clone()),
             ) {
@@ -578,7 +602,7 @@ impl Socket for StreamSocket {
         let local_endpoint = match state.as_ref() {
             State::Init(init_stream) => init_stream
                 .local_endpoint()
-                .unwrap_or(UNSPECIFIED_LOCAL_ENDPOINT),
+                .unwrap_or(unspecified_local_endpoint(self.ip_version)),
             State::Connecting(connecting_stream) => connecting_stream.local_endpoint(),
             State::Listen(listen_stream) => listen_stream.local_endpoint(),
             State::Connected(connected_stream) => connected_stream.local_endpoint(),
@@ -610,14 +634,14 @@ impl Socket for StreamSocket {
         }
 
         let MessageHeader {
-            control_message, ..
+            control_messages, ..
         } = message_header;
 
         // According to the Linux man pages, `EISCONN` _may_ be returned when the destination
         // address is specified for a connection-mode socket. In practice, the destination address
         // is simply ignored. We follow the same behavior as the Linux implementation to ignore it.
 
-        if control_message.is_some() {
+        if !control_messages.is_empty() {
             // TODO: Support sending control message
             warn!("sending control message is not supported");
         }
@@ -635,13 +659,18 @@ impl Socket for StreamSocket {
             warn!("unsupported flags: {:?}", flags);
         }
 
+        if flags.contains(SendRecvFlags::MSG_ERRQUEUE) {
+            // No extended errors are queued, since zero-copy transmission is not supported.
+            return_errno_with_message!(Errno::EAGAIN, "the error queue is empty");
+        }
+
         let (received_bytes, _) = self.recv(writer, flags)?;
 
         // TODO: Receive control message
 
         // According to <https://elixir.bootlin.com/linux/v6.0.9/source/net/ipv4/tcp.c#L2645>,
         // peer address is ignored for connected socket.
-        let message_header = MessageHeader::new(None, None);
+        let message_header = MessageHeader::new(None, Vec::new());
 
         Ok((received_bytes, message_header))
     }
@@ -653,6 +682,10 @@ impl Socket for StreamSocket {
                 options.socket.get_and_clear_sock_errors(socket_errors);
                 return Ok(());
             },
+            socket_zerocopy: ZeroCopy => {
+                socket_zerocopy.set(false);
+                return Ok(());
+            },
             _ => ()
         });
 
@@ -678,6 +711,14 @@ impl Socket for StreamSocket {
                 let keep_idle = options.tcp.keep_idle();
                 tcp_keep_idle.set(keep_idle);
             },
+            tcp_keep_interval: KeepInterval => {
+                let keep_interval = options.tcp.keep_interval();
+                tcp_keep_interval.set(keep_interval);
+            },
+            tcp_keep_count: KeepCount => {
+                let keep_count = options.tcp.keep_count();
+                tcp_keep_count.set(keep_count);
+            },
             tcp_window_clamp: WindowClamp => {
                 let window_clamp = options.tcp.window_clamp();
                 tcp_window_clamp.set(window_clamp);
@@ -693,9 +734,29 @@ impl Socket for StreamSocket {
     }
 
     fn set_option(&self, option: &dyn SocketOption) -> Result<()> {
+        match_sock_option_ref!(option, {
+            _socket_zerocopy: ZeroCopy => {
+                // TODO: Pin the user pages and hand them to the device driver directly. Until
+                // then, `MSG_ZEROCOPY` is ignored, as Linux does when `SO_ZEROCOPY` is not set.
+                return_errno_with_message!(
+                    Errno::EOPNOTSUPP,
+                    "zero-copy transmission is not supported"
+                );
+            },
+            _ => ()
+        });
+
         let (mut options, mut state) = self.update_connecting();
 
-        let need_iface_poll = match options.socket.set_option(option, state.as_mut()) {
+        let options_mut = &mut *options;
+        let mut state_with_options = StateWithOptions {
+            state: state.as_ref(),
+            tcp: &options_mut.tcp,
+        };
+        let need_iface_poll = match options_mut
+            .socket
+            .set_option(option, &mut state_with_options)
+        {
             Err(err) if err.error() == Errno::ENOPROTOOPT => {
                 do_tcp_setsockopt(option, &mut options, state.as_mut())?
             }
@@ -747,7 +808,31 @@ fn do_tcp_setsockopt(
             }
             options.tcp.set_keep_idle(*keepidle);
 
-            // TODO: Track when the socket becomes idle to actually support keep idle.
+            return Ok(state.set_raw_keep_alive(options.socket.keep_alive(), &options.tcp));
+        },
+        tcp_keep_interval: KeepInterval => {
+            const MIN_KEEP_INTERVAL: u32 = 1;
+            const MAX_KEEP_INTERVAL: u32 = 32767;
+
+            let keep_interval = tcp_keep_interval.get().unwrap();
+            if *keep_interval < MIN_KEEP_INTERVAL || *keep_interval > MAX_KEEP_INTERVAL {
+                return_errno_with_message!(Errno::EINVAL, "the keepalive interval is out of bounds");
+            }
+            options.tcp.set_keep_interval(*keep_interval);
+
+            return Ok(state.set_raw_keep_alive(options.socket.keep_alive(), &options.tcp));
+        },
+        tcp_keep_count: KeepCount => {
+            const MIN_KEEP_COUNT: u32 = 1;
+            const MAX_KEEP_COUNT: u32 = 127;
+
+            let keep_count = tcp_keep_count.get().unwrap();
+            if *keep_count < MIN_KEEP_COUNT || *keep_count > MAX_KEEP_COUNT {
+                return_errno_with_message!(Errno::EINVAL, "the keepalive count is out of bounds");
+            }
+            options.tcp.set_keep_count(*keep_count);
+
+            return Ok(state.set_raw_keep_alive(options.socket.keep_alive(), &options.tcp));
         },
         tcp_window_clamp: WindowClamp => {
             let window_clamp = tcp_window_clamp.get().unwrap();
@@ -761,6 +846,7 @@ fn do_tcp_setsockopt(
         tcp_congestion: Congestion => {
             let congestion = tcp_congestion.get().unwrap();
             options.tcp.set_congestion(*congestion);
+            state.set_raw_option(|raw_socket: &dyn RawTcpSetOption| raw_socket.set_congestion_control((*congestion).into()));
         },
         _ => return_errno_with_message!(Errno::ENOPROTOOPT, "the socket option to be set is unknown")
     });
@@ -784,6 +870,28 @@ impl State {
         }
     }
 
+    /// Sets the keepalive options of the raw socket according to the TCP options.
+    fn set_raw_keep_alive(&self, keep_alive: bool, tcp: &TcpOptionSet) -> NeedIfacePoll {
+        let (interval, timeout) = tcp.raw_keep_alive(keep_alive);
+
+        let set_keepalive = |raw_socket: &dyn RawTcpSetOption| {
+            raw_socket.set_timeout(timeout);
+            raw_socket.set_keep_alive(interval)
+        };
+
+        self.set_raw_option(set_keepalive)
+            .unwrap_or(NeedIfacePoll::FALSE)
+    }
+
+    /// Aborts the connection, if any, sending a RST to the peer.
+    fn abort(&self) {
+        match self {
+            State::Init(_) | State::Listen(_) => (),
+            State::Connecting(connecting_stream) => connecting_stream.abort(),
+            State::Connected(connected_stream) => connected_stream.abort(),
+        }
+    }
+
     fn iface(&self) -> Option<&Arc<Iface>> {
         match self {
             State::Init(_) => None,
@@ -794,25 +902,33 @@ impl State {
     }
 }
 
-impl SetSocketLevelOption for State {
-    fn set_keep_alive(&self, keep_alive: bool) -> NeedIfacePoll {
-        let interval = if keep_alive {
-            Some(KEEPALIVE_INTERVAL)
-        } else {
-            None
-        };
-
-        let set_keepalive = |raw_socket: &dyn RawTcpSetOption| raw_socket.set_keep_alive(interval);
+/// A [`State`] along with the TCP options, which are needed when setting socket-level options.
+struct StateWithOptions<'a> {
+    state: &'a State,
+    tcp: &'a TcpOptionSet,
+}
 
-        self.set_raw_option(set_keepalive)
-            .unwrap_or(NeedIfacePoll::FALSE)
+impl SetSocketLevelOption for StateWithOptions<'_> {
+    fn set_keep_alive(&self, keep_alive: bool) -> NeedIfacePoll {
+        self.state.set_raw_keep_alive(keep_alive, self.tcp)
     }
 }
 
 impl Drop for StreamSocket {
     fn drop(&mut self) {
+        let linger = self.options.get_mut().socket.linger();
         let state = self.state.get_mut().take();
 
+        // If lingering is enabled with a zero timeout, the connection is aborted immediately
+        // instead of being closed gracefully. See
+        // <https://man7.org/linux/man-pages/man7/socket.7.html>.
+        //
+        // TODO: Block until the remaining data is sent or the timeout expires if lingering is
+        // enabled with a non-zero timeout.
+        if linger.is_on() && linger.timeout().is_zero() {
+            state.abort();
+        }
+
         let iface_to_poll = state.iface().cloned();
 
         // Dropping the state will drop the sockets. This will trigger the socket close process (if
@@ -1,5 +1,7 @@
 // SPDX-License-Identifier: MPL-2.0
 
+use aster_bigtcp::{socket::RawTcpCongestionControl, time::Duration};
+
 use crate::prelude::*;
 
 #[derive(Debug, Clone, Copy, CopyGetters, Setters)]
@@ -9,12 +11,19 @@ pub struct TcpOptionSet {
     no_delay: bool,
     maxseg: u32,
     keep_idle: u32,
+    keep_interval: u32,
+    keep_count: u32,
     window_clamp: u32,
     congestion: CongestionControl,
 }
 
 pub const DEFAULT_MAXSEG: u32 = 536;
+/// The default values of the keepalive options.
+///
+/// The Linux values can be found at `/proc/sys/net/ipv4/tcp_keepalive_*`.
 pub const DEFAULT_KEEP_IDLE: u32 = 7200;
+pub const DEFAULT_KEEP_INTERVAL: u32 = 75;
+pub const DEFAULT_KEEP_COUNT: u32 = 9;
 pub const DEFAULT_WINDOW_CLAMP: u32 = 0x8000_0000;
 
 impl TcpOptionSet {
@@ -23,10 +32,33 @@ impl TcpOptionSet {
             no_delay: false,
             maxseg: DEFAULT_MAXSEG,
             keep_idle: DEFAULT_KEEP_IDLE,
+            keep_interval: DEFAULT_KEEP_INTERVAL,
+            keep_count: DEFAULT_KEEP_COUNT,
             window_clamp: DEFAULT_WINDOW_CLAMP,
             congestion: CongestionControl::Reno,
         }
     }
+
+    /// Returns the keepalive interval and the timeout of the raw socket.
+    ///
+    /// The raw socket sends keepalive probes whenever the connection has been idle for the
+    /// interval, and aborts the connection if nothing is received from the peer before the
+    /// timeout. Therefore, the connection is aborted if the probes are unanswered for
+    /// `keep_idle + keep_interval * keep_count` seconds, which matches Linux.
+    //
+    // TODO: Send the first probe after `keep_idle` seconds instead of `keep_interval` seconds.
+    pub fn raw_keep_alive(&self, keep_alive: bool) -> (Option<Duration>, Option<Duration>) {
+        if !keep_alive {
+            return (None, None);
+        }
+
+        let interval = Duration::from_secs(self.keep_interval as u64);
+        let timeout = Duration::from_secs(
+            self.keep_idle as u64 + self.keep_interval as u64 * self.keep_count as u64,
+        );
+
+        (Some(interval), Some(timeout))
+    }
 }
 
 impl Default for TcpOptionSet {
@@ -49,7 +81,7 @@ impl CongestionControl {
         let congestion = match name {
             Self::RENO => Self::Reno,
             Self::CUBIC => Self::Cubic,
-            _ => return_errno_with_message!(Errno::EINVAL, "unsupported congestion name"),
+            _ => return_errno_with_message!(Errno::ENOENT, "unsupported congestion name"),
         };
 
         Ok(congestion)
@@ -62,3 +94,12 @@ impl CongestionControl {
         }
     }
 }
+
+impl From<CongestionControl> for RawTcpCongestionControl {
+    fn from(value: CongestionControl) -> Self {
+        match value {
+            CongestionControl::Reno => Self::Reno,
+            CongestionControl::Cubic => Self::Cubic,
+        }
+    }
+}
@@ -0,0 +1,58 @@
+// SPDX-License-Identifier: MPL-2.0
+
+use crate::{net::socket::SocketAddr, prelude::*};
+
+/// The socket address of a netlink socket.
+///
+/// The port ID identifies a netlink socket. The port ID of the kernel is always zero. The groups
+/// field is a bit mask with every bit representing a netlink multicast group.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct NetlinkSocketAddr {
+    port: u32,
+    groups: u32,
+}
+
+impl NetlinkSocketAddr {
+    /// Creates a new netlink socket address.
+    pub const fn new(port: u32, groups: u32) -> Self {
+        Self { port, groups }
+    }
+
+    /// Creates the socket address of the kernel.
+    pub const fn new_kernel() -> Self {
+        Self::new(KERNEL_PORT, 0)
+    }
+
+    /// Returns the port ID.
+    pub const fn port(&self) -> u32 {
+        self.port
+    }
+
+    /// Returns the multicast groups mask.
+    pub const fn groups(&self) -> u32 {
+        self.groups
+    }
+}
+
+/// The port ID of the kernel.
+pub(super) const KERNEL_PORT: u32 = 0;
+
+impl TryFrom<SocketAddr> for NetlinkSocketAddr {
+    type Error = Error;
+
+    fn try_from(value: SocketAddr) -> Result<Self> {
+        match value {
+            SocketAddr::Netlink(netlink_addr) => Ok(netlink_addr),
+            _ => return_errno_with_message!(
+                Errno::EINVAL,
+                "the socket address is not a valid netlink socket address"
+            ),
+        }
+    }
+}
+
+impl From<NetlinkSocketAddr> for SocketAddr {
+    fn from(value: NetlinkSocketAddr) -> Self {
+        SocketAddr::Netlink(value)
+    }
+}
@@ -0,0 +1,450 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! The kernel side of the `NETLINK_AUDIT` protocol.
+//!
+//! The requests are handled synchronously when they are sent, and the replies are returned to the
+//! sending socket. Currently, the supported requests are:
+//!  - `AUDIT_GET` and `AUDIT_SET`, which query and configure the auditing subsystem, including
+//!    the registration of the audit daemon;
+//!  - `AUDIT_ADD_RULE`, `AUDIT_DEL_RULE`, and `AUDIT_LIST_RULES`, which manage the rules of the
+//!    exit filter list;
+//!  - `AUDIT_GET_FEATURE`, which queries the features;
+//!  - the user messages (e.g., `AUDIT_USER`), which are logged as records.
+//!
+//! The records are delivered to the socket of the audit daemon as messages whose types are the
+//! record types and whose payloads are the texts of the records.
+//!
+//! For more details, see <https://man7.org/linux/man-pages/man3/audit_open.3.html>.
+
+use core::mem::size_of;
+
+use super::super::{
+    message::{self, CMessageFlags, MessageWriter, RequestMessage},
+    NetlinkSocket,
+};
+use crate::{
+    prelude::*,
+    process::{credentials::capabilities::CapSet, posix_thread::AsPosixThread, Pid},
+    security::audit::{
+        self, AuditAction, AuditDaemon, AuditField, AuditRecord, AuditRule, FieldType, Operator,
+        MAX_FIELDS, SYSCALL_MASK_LEN,
+    },
+};
+
+/// Handles the request messages sent from the port of the socket, and returns the replies.
+pub(super) fn handle_requests(
+    bytes: &[u8],
+    port: u32,
+    socket: &Weak<NetlinkSocket>,
+) -> Vec<Vec<u8>> {
+    message::handle_requests(bytes, port, |request, writer| {
+        handle_request(request, port, socket, writer)
+    })
+}
+
+/// The types of the `NETLINK_AUDIT` messages.
+///
+/// See <https://elixir.bootlin.com/linux/v6.0.9/source/include/uapi/linux/audit.h>.
+#[repr(u16)]
+#[derive(Debug, Clone, Copy, TryFromInt, PartialEq, Eq)]
+#[allow(non_camel_case_types)]
+enum CAuditMessageType {
+    AUDIT_GET = 1000,
+    AUDIT_SET = 1001,
+    AUDIT_USER = 1005,
+    AUDIT_ADD_RULE = 1011,
+    AUDIT_DEL_RULE = 1012,
+    AUDIT_LIST_RULES = 1013,
+    AUDIT_GET_FEATURE = 1019,
+}
+
+/// The ranges of the types of the user messages.
+const USER_MESSAGE_TYPES: [core::ops::RangeInclusive<u16>; 2] = [1100..=1199, 2100..=2999];
+
+fn handle_request(
+    request: &RequestMessage,
+    port: u32,
+    socket: &Weak<NetlinkSocket>,
+    writer: &mut MessageWriter,
+) -> Result<()> {
+    let raw_type = request.header().type_;
+    if USER_MESSAGE_TYPES
+        .iter()
+        .any(|types| types.contains(&raw_type))
+    {
+        check_capability(CapSet::AUDIT_WRITE)?;
+        log_user_message(request);
+        return Ok(());
+    }
+
+    let type_ = CAuditMessageType::try_from(raw_type)
+        .map_err(|_| Error::with_message(Errno::EOPNOTSUPP, "the message type is not supported"))?;
+
+    match type_ {
+        CAuditMessageType::AUDIT_USER => {
+            check_capability(CapSet::AUDIT_WRITE)?;
+            log_user_message(request);
+            Ok(())
+        }
+        CAuditMessageType::AUDIT_GET => {
+            check_capability(CapSet::AUDIT_CONTROL)?;
+            get_status(request, port, writer);
+            Ok(())
+        }
+        CAuditMessageType::AUDIT_SET => {
+            check_capability(CapSet::AUDIT_CONTROL)?;
+            set_status(request, socket)
+        }
+        CAuditMessageType::AUDIT_ADD_RULE => {
+            check_capability(CapSet::AUDIT_CONTROL)?;
+            audit::add_rule(parse_rule(request.payload())?)
+        }
+        CAuditMessageType::AUDIT_DEL_RULE => {
+            check_capability(CapSet::AUDIT_CONTROL)?;
+            audit::del_rule(&parse_rule(request.payload())?)
+        }
+        CAuditMessageType::AUDIT_LIST_RULES => {
+            check_capability(CapSet::AUDIT_CONTROL)?;
+            list_rules(request, port, writer);
+            Ok(())
+        }
+        CAuditMessageType::AUDIT_GET_FEATURE => {
+            check_capability(CapSet::AUDIT_CONTROL)?;
+            get_features(request, port, writer);
+            Ok(())
+        }
+    }
+}
+
+fn check_capability(cap: CapSet) -> Result<()> {
+    let current = current_thread!();
+    let posix_thread = current.as_posix_thread().unwrap();
+
+    if !posix_thread.credentials().effective_capset().contains(cap) {
+        return_errno_with_message!(
+            Errno::EPERM,
+            "the audit request requires a capability that is not effective"
+        );
+    }
+
+    Ok(())
+}
+
+/// Reads a fixed-size header from the payload, and returns the header and the rest bytes.
+///
+/// If the payload is shorter than the header, the missing bytes are treated as zeros.
+fn read_header<T: Pod>(payload: &[u8]) -> (T, &[u8]) {
+    let mut header = T::new_zeroed();
+    let header_len = size_of::<T>().min(payload.len());
+    header.as_bytes_mut()[..header_len].copy_from_slice(&payload[..header_len]);
+
+    (header, &payload[header_len..])
+}
+
+//
+// Status
+//
+
+/// The status of the auditing subsystem (`struct audit_status`).
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Pod)]
+struct CAuditStatus {
+    /// Bit mask for valid entries
+    mask: u32,
+    /// 1 = enabled, 0 = disabled, 2 = locked
+    enabled: u32,
+    /// Failure-to-log action
+    failure: u32,
+    /// PID of the audit daemon
+    pid: u32,
+    /// Message rate limit (per second)
+    rate_limit: u32,
+    /// Waiting messages limit
+    backlog_limit: u32,
+    /// Messages lost
+    lost: u32,
+    /// Messages waiting in queue
+    backlog: u32,
+    /// Bitmap of kernel audit features
+    feature_bitmap: u32,
+    /// Message queue wait timeout
+    backlog_wait_time: u32,
+    /// Time spent waiting while message limit exceeded
+    backlog_wait_time_actual: u32,
+}
+
+bitflags! {
+    /// The valid fields in [`CAuditStatus`].
+    struct CStatusMask: u32 {
+        const AUDIT_STATUS_ENABLED = 0x0001;
+        const AUDIT_STATUS_FAILURE = 0x0002;
+        const AUDIT_STATUS_PID = 0x0004;
+        const AUDIT_STATUS_RATE_LIMIT = 0x0008;
+        const AUDIT_STATUS_BACKLOG_LIMIT = 0x0010;
+        const AUDIT_STATUS_BACKLOG_WAIT_TIME = 0x0020;
+        const AUDIT_STATUS_LOST = 0x0040;
+    }
+}
+
+/// The features of the kernel (`AUDIT_FEATURE_BITMAP_*`).
+const FEATURE_BITMAP_BACKLOG_LIMIT: u32 = 0x0001;
+
+fn get_status(request: &RequestMessage, port: u32, writer: &mut MessageWriter) {
+    let status = audit::status();
+    let c_status = CAuditStatus {
+        mask: 0,
+        enabled: status.enabled,
+        failure: status.failure,
+        pid: status.pid,
+        rate_limit: status.rate_limit,
+        backlog_limit: status.backlog_limit,
+        lost: status.lost,
+        backlog: status.backlog,
+        feature_bitmap: FEATURE_BITMAP_BACKLOG_LIMIT,
+        backlog_wait_time: 0,
+        backlog_wait_time_actual: 0,
+    };
+
+    writer.push_message(
+        CAuditMessageType::AUDIT_GET as u16,
+        CMessageFlags::empty(),
+        request.header().seq,
+        port,
+        |writer| writer.push_val(&c_status),
+    );
+}
+
+fn set_status(request: &RequestMessage, socket: &Weak<NetlinkSocket>) -> Result<()> {
+    let (c_status, _) = read_header::<CAuditStatus>(request.payload());
+    let mask = CStatusMask::from_bits_truncate(c_status.mask);
+
+    if mask.contains(CStatusMask::AUDIT_STATUS_ENABLED) {
+        audit::set_enabled(c_status.enabled)?;
+    }
+    if mask.contains(CStatusMask::AUDIT_STATUS_FAILURE) {
+        audit::set_failure(c_status.failure)?;
+    }
+    if mask.contains(CStatusMask::AUDIT_STATUS_PID) {
+        let pid = current!().pid();
+        match c_status.pid {
+            // Like Linux, setting the PID to zero unregisters the audit daemon.
+            0 => audit::clear_daemon(pid)?,
+            new_pid if new_pid == pid => audit::set_daemon(Box::new(SocketDaemon {
+                pid,
+                socket: socket.clone(),
+            }))?,
+            _ => return_errno_with_message!(
+                Errno::EINVAL,
+                "the PID of the audit daemon must be the PID of the sender"
+            ),
+        }
+    }
+    if mask.contains(CStatusMask::AUDIT_STATUS_RATE_LIMIT) {
+        audit::set_rate_limit(c_status.rate_limit)?;
+    }
+    if mask.contains(CStatusMask::AUDIT_STATUS_BACKLOG_LIMIT) {
+        audit::set_backlog_limit(c_status.backlog_limit)?;
+    }
+    if mask.contains(CStatusMask::AUDIT_STATUS_BACKLOG_WAIT_TIME) {
+        // The records are never waited to be delivered, so the wait time is ignored.
+        warn!("the backlog wait time is not supported");
+    }
+    if mask.contains(CStatusMask::AUDIT_STATUS_LOST) {
+        audit::reset_lost();
+    }
+
+    Ok(())
+}
+
+/// The audit daemon that receives the records with its `NETLINK_AUDIT` socket.
+struct SocketDaemon {
+    pid: Pid,
+    socket: Weak<NetlinkSocket>,
+}
+
+impl AuditDaemon for SocketDaemon {
+    fn pid(&self) -> Pid {
+        self.pid
+    }
+
+    fn is_alive(&self) -> bool {
+        self.socket.strong_count() > 0
+    }
+
+    fn deliver(&self, record: &AuditRecord) {
+        let Some(socket) = self.socket.upgrade() else {
+            return;
+        };
+
+        let mut writer = MessageWriter::new();
+        writer.push_message(record.type_(), CMessageFlags::empty(), 0, 0, |writer| {
+            writer.push_bytes(record.text().as_bytes())
+        });
+        socket.deliver(writer.into_bytes());
+    }
+}
+
+//
+// Features
+//
+
+/// The features of the kernel (`struct audit_features`).
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Pod)]
+struct CAuditFeatures {
+    version: u32,
+    /// The features that are changed
+    mask: u32,
+    /// The features that are enabled
+    features: u32,
+    /// The features that are locked
+    lock: u32,
+}
+
+const AUDIT_FEATURE_VERSION: u32 = 1;
+
+fn get_features(request: &RequestMessage, port: u32, writer: &mut MessageWriter) {
+    // None of the features (i.e., `AUDIT_FEATURE_ONLY_UNSET_LOGINUID` and
+    // `AUDIT_FEATURE_LOGINUID_IMMUTABLE`) are supported, since there is no login UID.
+    let features = CAuditFeatures {
+        version: AUDIT_FEATURE_VERSION,
+        mask: 0,
+        features: 0,
+        lock: 0,
+    };
+
+    writer.push_message(
+        CAuditMessageType::AUDIT_GET_FEATURE as u16,
+        CMessageFlags::empty(),
+        request.header().seq,
+        port,
+        |writer| writer.push_val(&features),
+    );
+}
+
+//
+// Rules
+//
+
+/// A rule in the messages (`struct audit_rule_data`), which is followed by `buflen` bytes of
+/// strings.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Pod)]
+struct CAuditRuleData {
+    /// The filter list (`AUDIT_FILTER_*`)
+    flags: u32,
+    /// The action (`AUDIT_NEVER` or `AUDIT_ALWAYS`)
+    action: u32,
+    field_count: u32,
+    /// The system calls
+    mask: [u32; SYSCALL_MASK_LEN],
+    fields: [u32; MAX_FIELDS],
+    values: [u32; MAX_FIELDS],
+    /// The comparison operators of the fields
+    fieldflags: [u32; MAX_FIELDS],
+    /// The total length of the strings
+    buflen: u32,
+}
+
+/// The exit filter list, which is the only supported filter list.
+const AUDIT_FILTER_EXIT: u32 = 0x04;
+
+/// The field type of the key of a rule.
+const AUDIT_FILTERKEY: u32 = 210;
+
+fn parse_rule(payload: &[u8]) -> Result<AuditRule> {
+    if payload.len() < size_of::<CAuditRuleData>() {
+        return_errno_with_message!(Errno::EINVAL, "the rule is too short");
+    }
+    // Like Linux, `buflen` is ignored and the strings are read from the rest of the payload.
+    let (data, mut buf) = read_header::<CAuditRuleData>(payload);
+
+    if data.flags != AUDIT_FILTER_EXIT {
+        return_errno_with_message!(Errno::EINVAL, "only the exit filter list is supported");
+    }
+    let action = AuditAction::try_from(data.action)
+        .map_err(|_| Error::with_message(Errno::EINVAL, "the action is invalid"))?;
+    if data.field_count as usize > MAX_FIELDS {
+        return_errno_with_message!(Errno::EINVAL, "the rule has too many fields");
+    }
+
+    let mut fields = Vec::new();
+    let mut key = None;
+    for i in 0..data.field_count as usize {
+        let op = Operator::try_from(data.fieldflags[i])
+            .map_err(|_| Error::with_message(Errno::EINVAL, "the operator is invalid"))?;
+        let value = data.values[i];
+
+        if data.fields[i] == AUDIT_FILTERKEY {
+            if op != Operator::AUDIT_EQUAL || key.is_some() {
+                return_errno_with_message!(Errno::EINVAL, "the key of the rule is invalid");
+            }
+            let Some((key_bytes, rest)) = buf.split_at_checked(value as usize) else {
+                return_errno_with_message!(Errno::EINVAL, "the key of the rule is truncated");
+            };
+            let key_str = core::str::from_utf8(key_bytes)
+                .map_err(|_| Error::with_message(Errno::EINVAL, "the key is not a valid string"))?;
+            key = Some(key_str.to_string());
+            buf = rest;
+            continue;
+        }
+
+        let type_ = FieldType::try_from(data.fields[i])
+            .map_err(|_| Error::with_message(Errno::EINVAL, "the field type is not supported"))?;
+        fields.push(AuditField::new(type_, op, value)?);
+    }
+
+    AuditRule::new(action, data.mask, fields, key)
+}
+
+fn list_rules(request: &RequestMessage, port: u32, writer: &mut MessageWriter) {
+    for rule in audit::rules() {
+        let mut data = CAuditRuleData::new_zeroed();
+        data.flags = AUDIT_FILTER_EXIT;
+        data.action = rule.action() as u32;
+        data.mask = *rule.syscall_mask();
+
+        let mut i = 0;
+        for field in rule.fields() {
+            data.fields[i] = field.type_ as u32;
+            data.values[i] = field.value;
+            data.fieldflags[i] = field.op as u32;
+            i += 1;
+        }
+        if let Some(key) = rule.key() {
+            data.fields[i] = AUDIT_FILTERKEY;
+            data.values[i] = key.len() as u32;
+            data.fieldflags[i] = Operator::AUDIT_EQUAL as u32;
+            data.buflen = key.len() as u32;
+            i += 1;
+        }
+        data.field_count = i as u32;
+
+        writer.push_message(
+            CAuditMessageType::AUDIT_LIST_RULES as u16,
+            CMessageFlags::MULTI,
+            request.header().seq,
+            port,
+            |writer| {
+                writer.push_val(&data);
+                writer.push_bytes(rule.key().unwrap_or_default().as_bytes());
+            },
+        );
+    }
+    writer.push_done(request, port);
+}
+
+//
+// User messages
+//
+
+fn log_user_message(request: &RequestMessage) {
+    // The message is a string, which may be terminated by a null byte.
+    let payload = request.payload();
+    let len = payload
+        .iter()
+        .position(|byte| *byte == 0)
+        .unwrap_or(payload.len());
+
+    audit::log_user_message(request.header().type_, &payload[..len]);
+}
@@ -0,0 +1,443 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! Netlink messages and attributes.
+//!
+//! A netlink message consists of a header (`struct nlmsghdr`) and a payload. The payload of most
+//! messages starts with a protocol-specific fixed-size header, followed by a list of attributes
+//! in the type-length-value format (`struct nlattr`). Both messages and attributes are aligned to
+//! 4 bytes.
+
+use core::mem::size_of;
+
+use crate::prelude::*;
+
+/// The header of a netlink message (`struct nlmsghdr`).
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Pod)]
+pub(super) struct CMessageHeader {
+    /// Length of the message, including the header
+    pub(super) len: u32,
+    /// Type of the message content
+    pub(super) type_: u16,
+    /// Additional flags
+    pub(super) flags: u16,
+    /// Sequence number
+    pub(super) seq: u32,
+    /// Port ID of the sender
+    pub(super) pid: u32,
+}
+
+pub(super) const MESSAGE_HEADER_LEN: usize = size_of::<CMessageHeader>();
+
+bitflags! {
+    /// The flags in the netlink message header.
+    pub(super) struct CMessageFlags: u16 {
+        /// It is a request message.
+        const REQUEST = 0x01;
+        /// It is a multipart message, terminated by `NLMSG_DONE`.
+        const MULTI = 0x02;
+        /// A reply with the error code is requested.
+        const ACK = 0x04;
+        /// The request is echoed.
+        const ECHO = 0x08;
+
+        // Modifiers to GET requests.
+        /// All the matching objects are returned.
+        const DUMP = 0x300;
+
+        // Modifiers to NEW requests.
+        /// The existing object is replaced.
+        const REPLACE = 0x100;
+        /// The object is not replaced if it already exists.
+        const EXCL = 0x200;
+        /// The object is created if it does not exist.
+        const CREATE = 0x400;
+        /// The object is added to the end of the list.
+        const APPEND = 0x800;
+
+        // Flags of acknowledgement messages.
+        /// The request payload is not included in the error message.
+        const CAPPED = 0x100;
+    }
+}
+
+/// The types of the netlink control messages.
+#[repr(u16)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[allow(non_camel_case_types)]
+pub(super) enum CControlMessageType {
+    /// Error or acknowledgement
+    NLMSG_ERROR = 2,
+    /// End of a multipart message
+    NLMSG_DONE = 3,
+}
+
+/// The minimum type of the protocol-specific messages.
+///
+/// The message types below it are reserved for the control messages.
+pub(super) const NLMSG_MIN_TYPE: u16 = 0x10;
+
+/// Aligns the length of a message or an attribute like `NLMSG_ALIGN` and `NLA_ALIGN`.
+pub(super) const fn align_len(len: usize) -> usize {
+    len.next_multiple_of(4)
+}
+
+/// A netlink message in a request.
+pub(super) struct RequestMessage<'a> {
+    header: CMessageHeader,
+    bytes: &'a [u8],
+}
+
+impl<'a> RequestMessage<'a> {
+    /// Returns the message header.
+    pub(super) fn header(&self) -> &CMessageHeader {
+        &self.header
+    }
+
+    /// Returns the message flags.
+    pub(super) fn flags(&self) -> CMessageFlags {
+        CMessageFlags::from_bits_truncate(self.header.flags)
+    }
+
+    /// Returns the payload, i.e., the bytes after the message header.
+    pub(super) fn payload(&self) -> &'a [u8] {
+        &self.bytes[MESSAGE_HEADER_LEN..]
+    }
+
+    /// Returns the bytes of the whole message, including the message header.
+    pub(super) fn as_bytes(&self) -> &'a [u8] {
+        self.bytes
+    }
+}
+
+/// Returns an iterator over the netlink messages in the bytes.
+///
+/// Similar to Linux, the iteration stops silently at the first malformed message.
+pub(super) fn iter_messages(bytes: &[u8]) -> impl Iterator<Item = RequestMessage<'_>> {
+    let mut remaining = bytes;
+
+    core::iter::from_fn(move || {
+        if remaining.len() < MESSAGE_HEADER_LEN {
+            return None;
+        }
+
+        let header = CMessageHeader::from_bytes(&remaining[..MESSAGE_HEADER_LEN]);
+        let len = header.len as usize;
+        if len < MESSAGE_HEADER_LEN || len > remaining.len() {
+            return None;
+        }
+
+        let message = RequestMessage {
+            header,
+            bytes: &remaining[..len],
+        };
+        remaining = &remaining[align_len(len).min(remaining.len())..];
+
+        Some(message)
+    })
+}
+
+/// Handles the request messages sent from the port with `handle_request`, and returns the
+/// replies.
+///
+/// Each reply corresponds to a request message that requires a reply, and should be delivered as
+/// a separate datagram.
+pub(super) fn handle_requests<F>(bytes: &[u8], port: u32, mut handle_request: F) -> Vec<Vec<u8>>
+where
+    F: FnMut(&RequestMessage, &mut MessageWriter) -> Result<()>,
+{
+    let mut replies = Vec::new();
+
+    for request in iter_messages(bytes) {
+        let flags = request.flags();
+
+        // Similar to Linux, the messages that are not requests and the control messages are
+        // ignored, but they are still acknowledged if required.
+        let type_ = request.header().type_;
+        let result = if !flags.contains(CMessageFlags::REQUEST) || type_ < NLMSG_MIN_TYPE {
+            Ok(false)
+        } else {
+            let mut writer = MessageWriter::new();
+            handle_request(&request, &mut writer).map(|()| {
+                let is_empty = writer.is_empty();
+                if !is_empty {
+                    replies.push(writer.into_bytes());
+                }
+                !is_empty
+            })
+        };
+
+        let mut writer = MessageWriter::new();
+        match result {
+            // The requests that have been replied are not acknowledged again.
+            Ok(true) => continue,
+            Ok(false) if !flags.contains(CMessageFlags::ACK) => continue,
+            Ok(false) => writer.push_error(&request, port, None),
+            Err(err) => writer.push_error(&request, port, Some(err.error())),
+        }
+        replies.push(writer.into_bytes());
+    }
+
+    replies
+}
+
+/// Parses the payload into a fixed-size header and the attributes after it.
+///
+/// If the payload is shorter than the fixed-size header, the missing bytes are treated as zeros.
+/// This is compatible with some old programs, which only send the first field of the header in
+/// dump requests.
+pub(super) fn parse_payload<T: Pod>(payload: &[u8]) -> Result<(T, Vec<Attribute<'_>>)> {
+    let mut header = T::new_zeroed();
+    let header_len = size_of::<T>().min(payload.len());
+    header.as_bytes_mut()[..header_len].copy_from_slice(&payload[..header_len]);
+
+    let attrs_offset = align_len(size_of::<T>()).min(payload.len());
+    let attrs = parse_attributes(&payload[attrs_offset..])?;
+
+    Ok((header, attrs))
+}
+
+/// The header of an attribute (`struct nlattr`).
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Pod)]
+struct CAttributeHeader {
+    /// Length of the attribute, including the header
+    len: u16,
+    /// Type of the attribute
+    type_: u16,
+}
+
+const ATTRIBUTE_HEADER_LEN: usize = size_of::<CAttributeHeader>();
+
+/// The bits in the attribute type that are used as flags.
+const ATTRIBUTE_TYPE_FLAGS: u16 = 0xc000;
+
+/// An attribute in a netlink message.
+#[derive(Debug, Clone, Copy)]
+pub(super) struct Attribute<'a> {
+    type_: u16,
+    value: &'a [u8],
+}
+
+impl<'a> Attribute<'a> {
+    /// Returns the attribute type, excluding the flags.
+    pub(super) fn type_(&self) -> u16 {
+        self.type_
+    }
+
+    /// Returns the attribute value as a plain old data type.
+    ///
+    /// This method returns `Err(EINVAL)` if the length of the value does not match.
+    pub(super) fn value_as<T: Pod>(&self) -> Result<T> {
+        if self.value.len() != size_of::<T>() {
+            return_errno_with_message!(Errno::EINVAL, "the attribute length is invalid");
+        }
+        Ok(T::from_bytes(self.value))
+    }
+
+    /// Returns the attribute value as a string, without the trailing null bytes.
+    pub(super) fn value_as_str(&self) -> Result<&'a str> {
+        let len = self
+            .value
+            .iter()
+            .position(|byte| *byte == 0)
+            .unwrap_or(self.value.len());
+        core::str::from_utf8(&self.value[..len])
+            .map_err(|_| Error::with_message(Errno::EINVAL, "the attribute is not a valid string"))
+    }
+}
+
+fn parse_attributes(mut bytes: &[u8]) -> Result<Vec<Attribute<'_>>> {
+    let mut attrs = Vec::new();
+
+    while bytes.len() >= ATTRIBUTE_HEADER_LEN {
+        let header = CAttributeHeader::from_bytes(&bytes[..ATTRIBUTE_HEADER_LEN]);
+        let len = header.len as usize;
+        if len < ATTRIBUTE_HEADER_LEN || len > bytes.len() {
+            return_errno_with_message!(Errno::EINVAL, "the attribute length is invalid");
+        }
+
+        attrs.push(Attribute {
+            type_: header.type_ & !ATTRIBUTE_TYPE_FLAGS,
+            value: &bytes[ATTRIBUTE_HEADER_LEN..len],
+        });
+        bytes = &bytes[align_len(len).min(bytes.len())..];
+    }
+
+    Ok(attrs)
+}
+
+/// A writer that builds netlink messages in a buffer.
+pub(super) struct MessageWriter {
+    buf: Vec<u8>,
+}
+
+impl MessageWriter {
+    pub(super) fn new() -> Self {
+        Self { buf: Vec::new() }
+    }
+
+    /// Appends a message, whose payload is written by `write_payload`.
+    pub(super) fn push_message<F>(
+        &mut self,
+        type_: u16,
+        flags: CMessageFlags,
+        seq: u32,
+        pid: u32,
+        write_payload: F,
+    ) where
+        F: FnOnce(&mut Self),
+    {
+        let start = self.buf.len();
+
+        let header = CMessageHeader {
+            len: 0,
+            type_,
+            flags: flags.bits(),
+            seq,
+            pid,
+        };
+        self.push_val(&header);
+        write_payload(self);
+
+        let len = (self.buf.len() - start) as u32;
+        self.buf[start..start + size_of::<u32>()].copy_from_slice(&len.to_ne_bytes());
+    }
+
+    /// Appends an error message (or an acknowledgement message if `error` is `None`) in reply
+    /// to the request message sent from the port.
+    pub(super) fn push_error(&mut self, request: &RequestMessage, port: u32, error: Option<Errno>) {
+        // Similar to Linux, the request payload is included only if the request fails.
+        let (flags, request_bytes) = match error {
+            Some(_) => (CMessageFlags::empty(), request.as_bytes()),
+            None => (
+                CMessageFlags::CAPPED,
+                &request.as_bytes()[..MESSAGE_HEADER_LEN],
+            ),
+        };
+        let error_code = error.map_or(0, |errno| -(errno as i32));
+
+        self.push_message(
+            CControlMessageType::NLMSG_ERROR as u16,
+            flags,
+            request.header().seq,
+            port,
+            |writer| {
+                writer.push_val(&error_code);
+                writer.push_bytes(request_bytes);
+            },
+        );
+    }
+
+    /// Appends a message that terminates a multipart message in reply to the request message
+    /// sent from the port.
+    pub(super) fn push_done(&mut self, request: &RequestMessage, port: u32) {
+        self.push_message(
+            CControlMessageType::NLMSG_DONE as u16,
+            CMessageFlags::MULTI,
+            request.header().seq,
+            port,
+            |writer| writer.push_val(&0i32),
+        );
+    }
+
+    /// Appends a value of a plain old data type, with the padding bytes.
+    pub(super) fn push_val<T: Pod>(&mut self, val: &T) {
+        self.push_bytes(val.as_bytes());
+    }
+
+    /// Appends the bytes, with the padding bytes.
+    pub(super) fn push_bytes(&mut self, bytes: &[u8]) {
+        self.buf.extend_from_slice(bytes);
+        self.buf.resize(align_len(self.buf.len()), 0);
+    }
+
+    /// Appends an attribute whose value consists of the bytes.
+    pub(super) fn push_attr(&mut self, type_: u16, value: &[u8]) {
+        let header = CAttributeHeader {
+            len: (ATTRIBUTE_HEADER_LEN + value.len()) as u16,
+            type_,
+        };
+        self.buf.extend_from_slice(header.as_bytes());
+        self.push_bytes(value);
+    }
+
+    /// Appends an attribute whose value is of a plain old data type.
+    pub(super) fn push_attr_val<T: Pod>(&mut self, type_: u16, value: &T) {
+        self.push_attr(type_, value.as_bytes());
+    }
+
+    /// Appends an attribute whose value is a null-terminated string.
+    pub(super) fn push_attr_str(&mut self, type_: u16, value: &str) {
+        let mut bytes = Vec::with_capacity(value.len() + 1);
+        bytes.extend_from_slice(value.as_bytes());
+        bytes.push(0);
+        self.push_attr(type_, &bytes);
+    }
+
+    pub(super) fn is_empty(&self) -> bool {
+        self.buf.is_empty()
+    }
+
+    pub(super) fn into_bytes(self) -> Vec<u8> {
+        self.buf
+    }
+}
+
+#[cfg(ktest)]
+mod test {
+    use ostd::prelude::*;
+
+    use super::*;
+
+    #[ktest]
+    fn test_write_and_parse() {
+        let mut writer = MessageWriter::new();
+        writer.push_message(NLMSG_MIN_TYPE, CMessageFlags::REQUEST, 1, 2, |writer| {
+            writer.push_val(&3u8);
+            writer.push_attr_str(1, "lo");
+            writer.push_attr_val(2, &4u32);
+        });
+        writer.push_message(NLMSG_MIN_TYPE + 1, CMessageFlags::empty(), 5, 6, |_| {});
+        let bytes = writer.into_bytes();
+
+        let messages = iter_messages(&bytes).collect::<Vec<_>>();
+        assert_eq!(messages.len(), 2);
+
+        let header = messages[0].header();
+        assert_eq!(header.type_, NLMSG_MIN_TYPE);
+        assert_eq!(messages[0].flags(), CMessageFlags::REQUEST);
+        assert_eq!((header.seq, header.pid), (1, 2));
+
+        let (val, attrs) = parse_payload::<u8>(messages[0].payload()).unwrap();
+        assert_eq!(val, 3);
+        assert_eq!(attrs.len(), 2);
+        assert_eq!(attrs[0].type_(), 1);
+        assert_eq!(attrs[0].value_as_str().unwrap(), "lo");
+        assert_eq!(attrs[1].type_(), 2);
+        assert_eq!(attrs[1].value_as::<u32>().unwrap(), 4);
+        assert!(attrs[1].value_as::<u16>().is_err());
+
+        let header = messages[1].header();
+        assert_eq!(header.len as usize, MESSAGE_HEADER_LEN);
+        assert_eq!((header.seq, header.pid), (5, 6));
+        assert!(messages[1].payload().is_empty());
+    }
+
+    #[ktest]
+    fn test_malformed_messages() {
+        let mut writer = MessageWriter::new();
+        writer.push_message(NLMSG_MIN_TYPE, CMessageFlags::REQUEST, 0, 0, |writer| {
+            writer.push_val(&0u32);
+        });
+        let mut bytes = writer.into_bytes();
+
+        // A truncated message stops the iteration.
+        bytes.extend_from_slice(&bytes.clone()[..MESSAGE_HEADER_LEN + 2]);
+        assert_eq!(iter_messages(&bytes).count(), 1);
+
+        // A truncated attribute is an error.
+        let attr = [8u8, 0, 1, 0, 0, 0];
+        assert!(parse_payload::<u32>(&[&[0u8; 4][..], &attr[..]].concat()).is_err());
+    }
+}
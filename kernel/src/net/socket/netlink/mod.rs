@@ -0,0 +1,69 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! Netlink sockets.
+//!
+//! Netlink sockets are used to transfer information between the kernel and user-space processes.
+//! Currently, the supported protocols are:
+//!  - `NETLINK_ROUTE`, which allows user-space programs to query and configure the network
+//!    interfaces, the IP addresses, and the routes;
+//!  - `NETLINK_AUDIT`, which allows user-space programs to configure the auditing subsystem and
+//!    to receive the audit records.
+//!
+//! For more details, see <https://www.man7.org/linux/man-pages/man7/netlink.7.html>.
+
+pub use self::{addr::NetlinkSocketAddr, socket::NetlinkSocket};
+use crate::prelude::*;
+
+mod addr;
+mod audit;
+mod message;
+mod route;
+mod socket;
+mod table;
+
+/// Netlink protocols.
+///
+/// See <https://elixir.bootlin.com/linux/v6.0.9/source/include/uapi/linux/netlink.h>.
+#[repr(i32)]
+#[derive(Debug, Clone, Copy, TryFromInt, PartialEq, Eq)]
+#[allow(non_camel_case_types)]
+pub enum NetlinkProtocol {
+    /// Routing/device hook
+    NETLINK_ROUTE = 0,
+    /// Unused number
+    NETLINK_UNUSED = 1,
+    /// Reserved for user mode socket protocols
+    NETLINK_USERSOCK = 2,
+    /// Unused number, formerly ip_queue
+    NETLINK_FIREWALL = 3,
+    /// Socket monitoring
+    NETLINK_SOCK_DIAG = 4,
+    /// Netfilter/iptables ULOG
+    NETLINK_NFLOG = 5,
+    /// IPsec
+    NETLINK_XFRM = 6,
+    /// SELinux event notifications
+    NETLINK_SELINUX = 7,
+    /// Open-iSCSI
+    NETLINK_ISCSI = 8,
+    /// Auditing
+    NETLINK_AUDIT = 9,
+    NETLINK_FIB_LOOKUP = 10,
+    NETLINK_CONNECTOR = 11,
+    /// Netfilter subsystem
+    NETLINK_NETFILTER = 12,
+    NETLINK_IP6_FW = 13,
+    /// DECnet routing messages
+    NETLINK_DNRTMSG = 14,
+    /// Kernel messages to userspace
+    NETLINK_KOBJECT_UEVENT = 15,
+    NETLINK_GENERIC = 16,
+    /// SCSI Transports
+    NETLINK_SCSITRANSPORT = 18,
+    NETLINK_ECRYPTFS = 19,
+    NETLINK_RDMA = 20,
+    /// Crypto layer
+    NETLINK_CRYPTO = 21,
+    /// SMC monitoring
+    NETLINK_SMC = 22,
+}
@@ -0,0 +1,605 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! The kernel side of the `NETLINK_ROUTE` protocol.
+//!
+//! The requests are handled synchronously when they are sent, and the replies are returned to the
+//! sending socket. Currently, the supported requests are:
+//!  - `RTM_GETLINK`, which queries the network interfaces;
+//!  - `RTM_GETADDR` and `RTM_NEWADDR`, which query and add the IPv4 addresses;
+//!  - `RTM_GETROUTE` and `RTM_NEWROUTE`, which query and add the IPv4 routes in the routing table.
+//!
+//! For more details, see <https://www.man7.org/linux/man-pages/man7/rtnetlink.7.html>.
+
+use core::mem::size_of;
+
+use aster_bigtcp::{
+    errors::IfaceConfigError,
+    wire::{IpAddress, IpCidr, IpVersion, Ipv4Address, Ipv4Cidr},
+};
+
+use super::super::message::{
+    self, parse_payload, Attribute, CMessageFlags, MessageWriter, RequestMessage,
+};
+use crate::{
+    net::iface::{
+        add_gateway_route, get_iface_by_index, is_loopback_iface, iter_ifaces_with_index, routes,
+        Iface, Route,
+    },
+    prelude::*,
+    process::{credentials::capabilities::CapSet, posix_thread::AsPosixThread},
+    util::net::CSocketAddrFamily,
+};
+
+/// Handles the request messages sent from the port, and returns the replies.
+pub(super) fn handle_requests(bytes: &[u8], port: u32) -> Vec<Vec<u8>> {
+    message::handle_requests(bytes, port, |request, writer| {
+        handle_request(request, port, writer)
+    })
+}
+
+/// The types of the `NETLINK_ROUTE` messages.
+///
+/// See <https://elixir.bootlin.com/linux/v6.0.9/source/include/uapi/linux/rtnetlink.h>.
+#[repr(u16)]
+#[derive(Debug, Clone, Copy, TryFromInt, PartialEq, Eq)]
+#[allow(non_camel_case_types)]
+enum CRouteMessageType {
+    RTM_NEWLINK = 16,
+    RTM_DELLINK = 17,
+    RTM_GETLINK = 18,
+    RTM_SETLINK = 19,
+    RTM_NEWADDR = 20,
+    RTM_DELADDR = 21,
+    RTM_GETADDR = 22,
+    RTM_NEWROUTE = 24,
+    RTM_DELROUTE = 25,
+    RTM_GETROUTE = 26,
+}
+
+fn handle_request(request: &RequestMessage, port: u32, writer: &mut MessageWriter) -> Result<()> {
+    let type_ = CRouteMessageType::try_from(request.header().type_)
+        .map_err(|_| Error::with_message(Errno::EOPNOTSUPP, "the message type is not supported"))?;
+    let is_dump = request.flags().intersects(CMessageFlags::DUMP);
+
+    match type_ {
+        CRouteMessageType::RTM_GETLINK if is_dump => dump_links(request, port, writer),
+        CRouteMessageType::RTM_GETLINK => get_link(request, port, writer),
+        CRouteMessageType::RTM_GETADDR if is_dump => dump_addrs(request, port, writer),
+        CRouteMessageType::RTM_GETROUTE if is_dump => dump_routes(request, port, writer),
+        CRouteMessageType::RTM_NEWADDR => {
+            check_net_admin()?;
+            new_addr(request)
+        }
+        CRouteMessageType::RTM_NEWROUTE => {
+            check_net_admin()?;
+            new_route(request)
+        }
+        _ => return_errno_with_message!(Errno::EOPNOTSUPP, "the request is not supported"),
+    }
+}
+
+fn check_net_admin() -> Result<()> {
+    let current = current_thread!();
+    let posix_thread = current.as_posix_thread().unwrap();
+
+    if !posix_thread
+        .credentials()
+        .effective_capset()
+        .contains(CapSet::NET_ADMIN)
+    {
+        return_errno_with_message!(
+            Errno::EPERM,
+            "configuring the network requires the CAP_NET_ADMIN capability"
+        );
+    }
+
+    Ok(())
+}
+
+fn get_iface(index: u32) -> Result<Arc<Iface>> {
+    get_iface_by_index(index)
+        .ok_or_else(|| Error::with_message(Errno::ENODEV, "the iface does not exist"))
+}
+
+fn find_attr<'a>(attrs: &[Attribute<'a>], type_: u16) -> Option<Attribute<'a>> {
+    attrs.iter().find(|attr| attr.type_() == type_).copied()
+}
+
+fn parse_ipv4_addr(attr: &Attribute) -> Result<Ipv4Address> {
+    attr.value_as::<[u8; 4]>().map(Ipv4Address::from)
+}
+
+/// The message flags of the replies.
+fn reply_flags(is_dump: bool) -> CMessageFlags {
+    if is_dump {
+        CMessageFlags::MULTI
+    } else {
+        CMessageFlags::empty()
+    }
+}
+
+//
+// Links
+//
+
+/// The header of the link messages (`struct ifinfomsg`).
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Pod)]
+struct CIfinfoMsg {
+    family: u8,
+    _pad: u8,
+    /// Device type
+    type_: u16,
+    /// Interface index
+    index: i32,
+    /// Device flags
+    flags: u32,
+    /// Change mask
+    change: u32,
+}
+
+/// The attribute types of the link messages.
+#[repr(u16)]
+#[derive(Debug, Clone, Copy)]
+#[allow(non_camel_case_types)]
+enum CLinkAttrType {
+    IFLA_ADDRESS = 1,
+    IFLA_BROADCAST = 2,
+    IFLA_IFNAME = 3,
+    IFLA_MTU = 4,
+    IFLA_TXQLEN = 13,
+    IFLA_OPERSTATE = 16,
+}
+
+// Device types.
+const ARPHRD_ETHER: u16 = 1;
+const ARPHRD_LOOPBACK: u16 = 772;
+const ARPHRD_NONE: u16 = 0xfffe;
+
+bitflags! {
+    /// Device flags.
+    struct CIfaceFlags: u32 {
+        const IFF_UP = 1 << 0;
+        const IFF_BROADCAST = 1 << 1;
+        const IFF_LOOPBACK = 1 << 3;
+        const IFF_POINTOPOINT = 1 << 4;
+        const IFF_RUNNING = 1 << 6;
+        const IFF_NOARP = 1 << 7;
+        const IFF_MULTICAST = 1 << 12;
+        const IFF_LOWER_UP = 1 << 16;
+    }
+}
+
+// Operational states.
+const IF_OPER_UNKNOWN: u8 = 0;
+const IF_OPER_UP: u8 = 6;
+
+// TODO: Get the MTUs from the devices.
+const ETHER_MTU: u32 = 1500;
+const LOOPBACK_MTU: u32 = 65536;
+
+const DEFAULT_TXQLEN: u32 = 1000;
+
+fn dump_links(request: &RequestMessage, port: u32, writer: &mut MessageWriter) -> Result<()> {
+    for (index, iface) in iter_ifaces_with_index() {
+        push_link(request, port, index, &iface, true, writer);
+    }
+    writer.push_done(request, port);
+
+    Ok(())
+}
+
+fn get_link(request: &RequestMessage, port: u32, writer: &mut MessageWriter) -> Result<()> {
+    let (ifinfo, attrs) = parse_payload::<CIfinfoMsg>(request.payload())?;
+
+    let (index, iface) = if ifinfo.index > 0 {
+        let index = ifinfo.index as u32;
+        (index, get_iface(index)?)
+    } else if let Some(attr) = find_attr(&attrs, CLinkAttrType::IFLA_IFNAME as u16) {
+        let name = attr.value_as_str()?;
+        iter_ifaces_with_index()
+            .find(|(_, iface)| iface.name() == name)
+            .ok_or_else(|| Error::with_message(Errno::ENODEV, "the iface does not exist"))?
+    } else {
+        return_errno_with_message!(Errno::EINVAL, "neither the index nor the name is specified");
+    };
+
+    push_link(request, port, index, &iface, false, writer);
+
+    Ok(())
+}
+
+fn push_link(
+    request: &RequestMessage,
+    port: u32,
+    index: u32,
+    iface: &Iface,
+    is_dump: bool,
+    writer: &mut MessageWriter,
+) {
+    let hardware_addr = iface.hardware_addr();
+
+    let (type_, flags, mtu, oper_state) = if hardware_addr.is_some() {
+        let flags = CIfaceFlags::IFF_UP
+            | CIfaceFlags::IFF_BROADCAST
+            | CIfaceFlags::IFF_RUNNING
+            | CIfaceFlags::IFF_MULTICAST
+            | CIfaceFlags::IFF_LOWER_UP;
+        (ARPHRD_ETHER, flags, ETHER_MTU, IF_OPER_UP)
+    } else if is_loopback_iface(index) {
+        let flags = CIfaceFlags::IFF_UP
+            | CIfaceFlags::IFF_LOOPBACK
+            | CIfaceFlags::IFF_RUNNING
+            | CIfaceFlags::IFF_LOWER_UP;
+        (ARPHRD_LOOPBACK, flags, LOOPBACK_MTU, IF_OPER_UNKNOWN)
+    } else {
+        // Other ifaces without hardware addresses are TUN ifaces.
+        let flags = CIfaceFlags::IFF_UP
+            | CIfaceFlags::IFF_POINTOPOINT
+            | CIfaceFlags::IFF_RUNNING
+            | CIfaceFlags::IFF_NOARP
+            | CIfaceFlags::IFF_MULTICAST
+            | CIfaceFlags::IFF_LOWER_UP;
+        (ARPHRD_NONE, flags, ETHER_MTU, IF_OPER_UNKNOWN)
+    };
+    let (addr, broadcast) = match hardware_addr {
+        Some(ether_addr) => (ether_addr.0, [0xff; 6]),
+        None => ([0; 6], [0; 6]),
+    };
+
+    let ifinfo = CIfinfoMsg {
+        family: CSocketAddrFamily::AF_UNSPEC as u8,
+        _pad: 0,
+        type_,
+        index: index as i32,
+        flags: flags.bits(),
+        change: 0,
+    };
+
+    writer.push_message(
+        CRouteMessageType::RTM_NEWLINK as u16,
+        reply_flags(is_dump),
+        request.header().seq,
+        port,
+        |writer| {
+            writer.push_val(&ifinfo);
+            writer.push_attr_str(CLinkAttrType::IFLA_IFNAME as u16, iface.name());
+            writer.push_attr_val(CLinkAttrType::IFLA_MTU as u16, &mtu);
+            writer.push_attr_val(CLinkAttrType::IFLA_TXQLEN as u16, &DEFAULT_TXQLEN);
+            writer.push_attr_val(CLinkAttrType::IFLA_OPERSTATE as u16, &oper_state);
+            writer.push_attr(CLinkAttrType::IFLA_ADDRESS as u16, &addr);
+            writer.push_attr(CLinkAttrType::IFLA_BROADCAST as u16, &broadcast);
+        },
+    );
+}
+
+//
+// Addresses
+//
+
+/// The header of the address messages (`struct ifaddrmsg`).
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Pod)]
+struct CIfaddrMsg {
+    family: u8,
+    /// The prefix length
+    prefix_len: u8,
+    /// Address flags
+    flags: u8,
+    /// Address scope
+    scope: u8,
+    /// Interface index
+    index: u32,
+}
+
+/// The attribute types of the address messages.
+#[repr(u16)]
+#[derive(Debug, Clone, Copy)]
+#[allow(non_camel_case_types)]
+enum CAddrAttrType {
+    IFA_ADDRESS = 1,
+    IFA_LOCAL = 2,
+    IFA_LABEL = 3,
+    IFA_BROADCAST = 4,
+}
+
+/// The flag of the permanent addresses.
+const IFA_F_PERMANENT: u8 = 0x80;
+
+// Scopes of addresses and routes.
+const RT_SCOPE_UNIVERSE: u8 = 0;
+const RT_SCOPE_LINK: u8 = 253;
+const RT_SCOPE_HOST: u8 = 254;
+
+fn dump_addrs(request: &RequestMessage, port: u32, writer: &mut MessageWriter) -> Result<()> {
+    let (ifaddr, _) = parse_payload::<CIfaddrMsg>(request.payload())?;
+
+    if is_ipv4_family(ifaddr.family) {
+        for (index, iface) in iter_ifaces_with_index() {
+            for ipv4_cidr in iface.ipv4_cidrs() {
+                push_addr(request, port, index, &iface, &ipv4_cidr, writer);
+            }
+        }
+    }
+    writer.push_done(request, port);
+
+    Ok(())
+}
+
+fn push_addr(
+    request: &RequestMessage,
+    port: u32,
+    index: u32,
+    iface: &Iface,
+    ipv4_cidr: &Ipv4Cidr,
+    writer: &mut MessageWriter,
+) {
+    let addr = ipv4_cidr.address();
+    let scope = if addr.is_loopback() {
+        RT_SCOPE_HOST
+    } else {
+        RT_SCOPE_UNIVERSE
+    };
+
+    let ifaddr = CIfaddrMsg {
+        family: CSocketAddrFamily::AF_INET as u8,
+        prefix_len: ipv4_cidr.prefix_len(),
+        flags: IFA_F_PERMANENT,
+        scope,
+        index,
+    };
+
+    writer.push_message(
+        CRouteMessageType::RTM_NEWADDR as u16,
+        CMessageFlags::MULTI,
+        request.header().seq,
+        port,
+        |writer| {
+            writer.push_val(&ifaddr);
+            writer.push_attr(CAddrAttrType::IFA_ADDRESS as u16, &addr.octets());
+            writer.push_attr(CAddrAttrType::IFA_LOCAL as u16, &addr.octets());
+            if iface.hardware_addr().is_some() {
+                if let Some(broadcast) = ipv4_cidr.broadcast() {
+                    writer.push_attr(CAddrAttrType::IFA_BROADCAST as u16, &broadcast.octets());
+                }
+            }
+            writer.push_attr_str(CAddrAttrType::IFA_LABEL as u16, iface.name());
+        },
+    );
+}
+
+fn new_addr(request: &RequestMessage) -> Result<()> {
+    let payload = request.payload();
+    if payload.len() < size_of::<CIfaddrMsg>() {
+        return_errno_with_message!(Errno::EINVAL, "the message is too short");
+    }
+    let (ifaddr, attrs) = parse_payload::<CIfaddrMsg>(payload)?;
+
+    if ifaddr.family != CSocketAddrFamily::AF_INET as u8 {
+        return_errno_with_message!(Errno::EAFNOSUPPORT, "only IPv4 addresses are supported");
+    }
+    if ifaddr.prefix_len > 32 {
+        return_errno_with_message!(Errno::EINVAL, "the prefix length is invalid");
+    }
+
+    // Similar to Linux, `IFA_LOCAL` is the address of the iface, and `IFA_ADDRESS` is used as
+    // the local address if `IFA_LOCAL` is absent.
+    let attr = find_attr(&attrs, CAddrAttrType::IFA_LOCAL as u16)
+        .or_else(|| find_attr(&attrs, CAddrAttrType::IFA_ADDRESS as u16))
+        .ok_or_else(|| Error::with_message(Errno::EINVAL, "the address is not specified"))?;
+    let addr = parse_ipv4_addr(&attr)?;
+
+    let iface = get_iface(ifaddr.index)?;
+    iface
+        .add_ipv4_cidr(Ipv4Cidr::new(addr, ifaddr.prefix_len))
+        .map_err(|err| match err {
+            IfaceConfigError::Exists => {
+                Error::with_message(Errno::EEXIST, "the address already exists")
+            }
+            IfaceConfigError::Full => {
+                Error::with_message(Errno::ENOSPC, "the iface cannot have more addresses")
+            }
+        })
+}
+
+//
+// Routes
+//
+
+/// The header of the route messages (`struct rtmsg`).
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Pod)]
+struct CRtMsg {
+    family: u8,
+    /// The prefix length of the destination
+    dst_len: u8,
+    /// The prefix length of the source
+    src_len: u8,
+    /// TOS filter
+    tos: u8,
+    /// Routing table ID
+    table: u8,
+    /// Routing protocol
+    protocol: u8,
+    /// Distance to the destination
+    scope: u8,
+    /// Route type
+    type_: u8,
+    /// Route flags
+    flags: u32,
+}
+
+/// The attribute types of the route messages.
+#[repr(u16)]
+#[derive(Debug, Clone, Copy)]
+#[allow(non_camel_case_types)]
+enum CRouteAttrType {
+    RTA_DST = 1,
+    RTA_OIF = 4,
+    RTA_GATEWAY = 5,
+    RTA_PRIORITY = 6,
+    RTA_PREFSRC = 7,
+    RTA_TABLE = 15,
+}
+
+// Routing table IDs.
+const RT_TABLE_UNSPEC: u32 = 0;
+const RT_TABLE_MAIN: u32 = 254;
+
+// Routing protocols.
+const RTPROT_KERNEL: u8 = 2;
+const RTPROT_BOOT: u8 = 3;
+
+/// The type of the unicast routes.
+const RTN_UNICAST: u8 = 1;
+
+fn dump_routes(request: &RequestMessage, port: u32, writer: &mut MessageWriter) -> Result<()> {
+    let (rtmsg, _) = parse_payload::<CRtMsg>(request.payload())?;
+
+    if is_ipv4_family(rtmsg.family) {
+        for route in routes() {
+            if route.dst.version() == IpVersion::Ipv4 {
+                push_route(request, port, &route, writer);
+            }
+        }
+    }
+    writer.push_done(request, port);
+
+    Ok(())
+}
+
+fn push_route(request: &RequestMessage, port: u32, route: &Route, writer: &mut MessageWriter) {
+    let (protocol, scope) = if route.gateway.is_some() {
+        (RTPROT_BOOT, RT_SCOPE_UNIVERSE)
+    } else {
+        (RTPROT_KERNEL, RT_SCOPE_LINK)
+    };
+
+    let rtmsg = CRtMsg {
+        family: CSocketAddrFamily::AF_INET as u8,
+        dst_len: route.dst.prefix_len(),
+        src_len: 0,
+        tos: 0,
+        table: RT_TABLE_MAIN as u8,
+        protocol,
+        scope,
+        type_: RTN_UNICAST,
+        flags: 0,
+    };
+
+    writer.push_message(
+        CRouteMessageType::RTM_NEWROUTE as u16,
+        CMessageFlags::MULTI,
+        request.header().seq,
+        port,
+        |writer| {
+            writer.push_val(&rtmsg);
+            writer.push_attr_val(CRouteAttrType::RTA_TABLE as u16, &RT_TABLE_MAIN);
+            if route.dst.prefix_len() != 0 {
+                writer.push_attr(
+                    CRouteAttrType::RTA_DST as u16,
+                    &ip_addr_octets(&route.dst.address()),
+                );
+            }
+            if route.metric != 0 {
+                writer.push_attr_val(CRouteAttrType::RTA_PRIORITY as u16, &route.metric);
+            }
+            if let Some(pref_src) = route.pref_src {
+                writer.push_attr(
+                    CRouteAttrType::RTA_PREFSRC as u16,
+                    &ip_addr_octets(&pref_src),
+                );
+            }
+            if let Some(gateway) = route.gateway {
+                writer.push_attr(
+                    CRouteAttrType::RTA_GATEWAY as u16,
+                    &ip_addr_octets(&gateway),
+                );
+            }
+            writer.push_attr_val(CRouteAttrType::RTA_OIF as u16, &route.ifindex);
+        },
+    );
+}
+
+fn new_route(request: &RequestMessage) -> Result<()> {
+    let payload = request.payload();
+    if payload.len() < size_of::<CRtMsg>() {
+        return_errno_with_message!(Errno::EINVAL, "the message is too short");
+    }
+    let (rtmsg, attrs) = parse_payload::<CRtMsg>(payload)?;
+
+    if rtmsg.family != CSocketAddrFamily::AF_INET as u8 {
+        return_errno_with_message!(Errno::EAFNOSUPPORT, "only IPv4 routes are supported");
+    }
+    if rtmsg.type_ != RTN_UNICAST {
+        return_errno_with_message!(Errno::EOPNOTSUPP, "only unicast routes are supported");
+    }
+    if rtmsg.dst_len > 32 {
+        return_errno_with_message!(Errno::EINVAL, "the prefix length is invalid");
+    }
+
+    let table = match find_attr(&attrs, CRouteAttrType::RTA_TABLE as u16) {
+        Some(attr) => attr.value_as::<u32>()?,
+        None => rtmsg.table as u32,
+    };
+    if table != RT_TABLE_MAIN && table != RT_TABLE_UNSPEC {
+        return_errno_with_message!(
+            Errno::EOPNOTSUPP,
+            "only the main routing table is supported"
+        );
+    }
+
+    let dst_addr = match find_attr(&attrs, CRouteAttrType::RTA_DST as u16) {
+        Some(attr) => parse_ipv4_addr(&attr)?,
+        None => Ipv4Address::UNSPECIFIED,
+    };
+    let dst = Ipv4Cidr::new(dst_addr, rtmsg.dst_len);
+    if dst.network().address() != dst_addr {
+        return_errno_with_message!(Errno::EINVAL, "the prefix is invalid for the prefix length");
+    }
+
+    let Some(attr) = find_attr(&attrs, CRouteAttrType::RTA_GATEWAY as u16) else {
+        return_errno_with_message!(
+            Errno::EOPNOTSUPP,
+            "the routes without gateways are not supported"
+        );
+    };
+    let gateway = parse_ipv4_addr(&attr)?;
+
+    let is_reachable = |iface: &Iface| {
+        iface
+            .ipv4_cidrs()
+            .iter()
+            .any(|ipv4_cidr| ipv4_cidr.contains_addr(&gateway))
+    };
+    let ifindex = match find_attr(&attrs, CRouteAttrType::RTA_OIF as u16) {
+        Some(attr) => {
+            let ifindex = attr.value_as::<u32>()?;
+            is_reachable(&get_iface(ifindex)?).then_some(ifindex)
+        }
+        None => iter_ifaces_with_index()
+            .find(|(_, iface)| is_reachable(iface))
+            .map(|(ifindex, _)| ifindex),
+    }
+    .ok_or_else(|| Error::with_message(Errno::ENETUNREACH, "the gateway is unreachable"))?;
+
+    let metric = match find_attr(&attrs, CRouteAttrType::RTA_PRIORITY as u16) {
+        Some(attr) => attr.value_as::<u32>()?,
+        None => 0,
+    };
+
+    add_gateway_route(IpCidr::Ipv4(dst), IpAddress::Ipv4(gateway), ifindex, metric)
+}
+
+/// Returns the octets of the IP address in network byte order.
+fn ip_addr_octets(ip_addr: &IpAddress) -> Vec<u8> {
+    match ip_addr {
+        IpAddress::Ipv4(ipv4_addr) => ipv4_addr.octets().to_vec(),
+        IpAddress::Ipv6(ipv6_addr) => ipv6_addr.octets().to_vec(),
+    }
+}
+
+/// Returns whether the address family in a dump request matches IPv4.
+fn is_ipv4_family(family: u8) -> bool {
+    family == CSocketAddrFamily::AF_UNSPEC as u8 || family == CSocketAddrFamily::AF_INET as u8
+}
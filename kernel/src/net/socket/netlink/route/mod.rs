@@ -0,0 +1,11 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! The `NETLINK_ROUTE` protocol.
+
+pub(super) use self::kernel::handle_requests;
+use super::table::PortTable;
+
+mod kernel;
+
+/// The port IDs bound to the `NETLINK_ROUTE` sockets.
+pub(super) static PORT_TABLE: PortTable = PortTable::new();
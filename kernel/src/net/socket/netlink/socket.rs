@@ -0,0 +1,422 @@
+// SPDX-License-Identifier: MPL-2.0
+
+use alloc::collections::vec_deque::VecDeque;
+use core::sync::atomic::{AtomicBool, Ordering};
+
+use super::{
+    addr::{NetlinkSocketAddr, KERNEL_PORT},
+    audit, route,
+    table::{BoundPort, PortTable},
+    NetlinkProtocol,
+};
+use crate::{
+    events::IoEvents,
+    fs::{
+        file_handle::FileLike,
+        utils::{InodeMode, Metadata, StatusFlags},
+    },
+    match_sock_option_mut,
+    net::socket::{
+        options::{Error as SocketError, SocketOption},
+        util::{
+            options::{SetSocketLevelOption, SocketOptionSet},
+            send_recv_flags::SendRecvFlags,
+            socket_addr::SocketAddr,
+            MessageHeader,
+        },
+        Socket,
+    },
+    prelude::*,
+    process::signal::{PollHandle, Pollable, Pollee},
+    util::{MultiRead, MultiWrite},
+};
+
+/// The length of the message header that is reserved in the send buffer.
+///
+/// Linux reserves space for the `struct sk_buff` overhead, so a message cannot be as large as the
+/// send buffer. We reserve space for the netlink message header here as an approximation.
+const SEND_BUF_OVERHEAD: usize = 32;
+
+#[derive(Debug, Clone)]
+struct OptionSet {
+    socket: SocketOptionSet,
+}
+
+impl OptionSet {
+    fn new() -> Self {
+        let socket = SocketOptionSet::new_netlink();
+        OptionSet { socket }
+    }
+}
+
+/// The netlink protocols whose kernel sides are implemented.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum SupportedProtocol {
+    Route,
+    Audit,
+}
+
+impl SupportedProtocol {
+    fn port_table(self) -> &'static PortTable {
+        match self {
+            Self::Route => &route::PORT_TABLE,
+            Self::Audit => &audit::PORT_TABLE,
+        }
+    }
+}
+
+impl TryFrom<NetlinkProtocol> for SupportedProtocol {
+    type Error = Error;
+
+    fn try_from(protocol: NetlinkProtocol) -> Result<Self> {
+        match protocol {
+            NetlinkProtocol::NETLINK_ROUTE => Ok(Self::Route),
+            NetlinkProtocol::NETLINK_AUDIT => Ok(Self::Audit),
+            _ => return_errno_with_message!(
+                Errno::EPROTONOSUPPORT,
+                "the netlink protocol is not supported"
+            ),
+        }
+    }
+}
+
+/// A netlink socket.
+pub struct NetlinkSocket {
+    protocol: SupportedProtocol,
+    options: RwLock<OptionSet>,
+    inner: RwLock<Inner>,
+    /// The replies from the kernel, each of which is a datagram.
+    receive_queue: Mutex<VecDeque<Vec<u8>>>,
+    is_nonblocking: AtomicBool,
+    pollee: Pollee,
+    weak_self: Weak<NetlinkSocket>,
+}
+
+struct Inner {
+    port_table: &'static PortTable,
+    bound_port: Option<BoundPort>,
+    remote_addr: NetlinkSocketAddr,
+    groups: u32,
+}
+
+impl Inner {
+    fn bind_ephemeral(&mut self) -> Result<u32> {
+        if let Some(bound_port) = self.bound_port.as_ref() {
+            return Ok(bound_port.port());
+        }
+
+        let bound_port = self.port_table.bind_ephemeral()?;
+        let port = bound_port.port();
+        self.bound_port = Some(bound_port);
+
+        Ok(port)
+    }
+
+    fn local_addr(&self) -> NetlinkSocketAddr {
+        let port = self
+            .bound_port
+            .as_ref()
+            .map(BoundPort::port)
+            .unwrap_or_default();
+        NetlinkSocketAddr::new(port, self.groups)
+    }
+}
+
+impl NetlinkSocket {
+    /// Creates a netlink socket of the protocol.
+    ///
+    /// This method fails with `EPROTONOSUPPORT` if the protocol is not supported.
+    pub fn new(protocol: NetlinkProtocol, is_nonblocking: bool) -> Result<Arc<Self>> {
+        let protocol = SupportedProtocol::try_from(protocol)?;
+        let inner = Inner {
+            port_table: protocol.port_table(),
+            bound_port: None,
+            remote_addr: NetlinkSocketAddr::new_kernel(),
+            groups: 0,
+        };
+
+        Ok(Arc::new_cyclic(|weak_self| Self {
+            protocol,
+            options: RwLock::new(OptionSet::new()),
+            inner: RwLock::new(inner),
+            receive_queue: Mutex::new(VecDeque::new()),
+            is_nonblocking: AtomicBool::new(is_nonblocking),
+            pollee: Pollee::new(),
+            weak_self: weak_self.clone(),
+        }))
+    }
+
+    pub fn is_nonblocking(&self) -> bool {
+        self.is_nonblocking.load(Ordering::Relaxed)
+    }
+
+    pub fn set_nonblocking(&self, is_nonblocking: bool) {
+        self.is_nonblocking.store(is_nonblocking, Ordering::Relaxed);
+    }
+
+    /// Delivers a datagram from the kernel that is not a reply to any request.
+    pub fn deliver(&self, datagram: Vec<u8>) {
+        self.receive_queue.lock().push_back(datagram);
+        self.pollee.notify(IoEvents::IN);
+    }
+
+    fn try_send(
+        &self,
+        reader: &mut dyn MultiRead,
+        remote_addr: &NetlinkSocketAddr,
+    ) -> Result<usize> {
+        if remote_addr.port() != KERNEL_PORT {
+            // TODO: Support sending messages to other netlink sockets.
+            return_errno_with_message!(
+                Errno::ECONNREFUSED,
+                "sending messages to user-space sockets is not supported"
+            );
+        }
+        if remote_addr.groups() != 0 {
+            // TODO: Support multicast.
+            warn!("sending messages to multicast groups is not supported");
+        }
+
+        let port = self.inner.write().bind_ephemeral()?;
+
+        let len = reader.sum_lens();
+        let max_len =
+            (self.options.read().socket.send_buf() as usize).saturating_sub(SEND_BUF_OVERHEAD);
+        if len > max_len {
+            return_errno_with_message!(Errno::EMSGSIZE, "the message is too large");
+        }
+
+        let mut buf = vec![0u8; len];
+        let read_len = reader.read(&mut VmWriter::from(buf.as_mut_slice()))?;
+        buf.truncate(read_len);
+
+        let replies = match self.protocol {
+            SupportedProtocol::Route => route::handle_requests(&buf, port),
+            SupportedProtocol::Audit => audit::handle_requests(&buf, port, &self.weak_self),
+        };
+        if !replies.is_empty() {
+            self.receive_queue.lock().extend(replies);
+            self.pollee.notify(IoEvents::IN);
+        }
+
+        Ok(read_len)
+    }
+
+    fn try_recv(&self, writer: &mut dyn MultiWrite, flags: SendRecvFlags) -> Result<usize> {
+        let mut receive_queue = self.receive_queue.lock();
+
+        let Some(datagram) = receive_queue.front() else {
+            return_errno_with_message!(Errno::EAGAIN, "the receive queue is empty");
+        };
+
+        let copy_len = datagram.len().min(writer.sum_lens());
+        writer.write(&mut VmReader::from(&datagram[..copy_len]))?;
+
+        let len = if flags.contains(SendRecvFlags::MSG_TRUNC) {
+            datagram.len()
+        } else {
+            copy_len
+        };
+
+        // Similar to other datagram sockets, the rest of the datagram is discarded if the buffer
+        // is too small, unless `MSG_PEEK` is specified.
+        if !flags.contains(SendRecvFlags::MSG_PEEK) {
+            receive_queue.pop_front();
+            self.pollee.invalidate();
+        }
+
+        Ok(len)
+    }
+
+    fn recv(&self, writer: &mut dyn MultiWrite, flags: SendRecvFlags) -> Result<usize> {
+        if self.is_nonblocking() || flags.contains(SendRecvFlags::MSG_DONTWAIT) {
+            self.try_recv(writer, flags)
+        } else {
+            self.wait_events(IoEvents::IN, None, || self.try_recv(writer, flags))
+        }
+    }
+
+    fn check_io_events(&self) -> IoEvents {
+        let mut events = IoEvents::OUT;
+
+        if !self.receive_queue.lock().is_empty() {
+            events |= IoEvents::IN;
+        }
+
+        events
+    }
+}
+
+impl Pollable for NetlinkSocket {
+    fn poll(&self, mask: IoEvents, poller: Option<&mut PollHandle>) -> IoEvents {
+        self.pollee
+            .poll_with(mask, poller, || self.check_io_events())
+    }
+}
+
+impl FileLike for NetlinkSocket {
+    fn read(&self, writer: &mut VmWriter) -> Result<usize> {
+        // TODO: set correct flags
+        let flags = SendRecvFlags::empty();
+        self.recv(writer, flags)
+    }
+
+    fn write(&self, reader: &mut VmReader) -> Result<usize> {
+        let remote_addr = self.inner.read().remote_addr;
+        self.try_send(reader, &remote_addr)
+    }
+
+    fn as_socket(&self) -> Option<&dyn Socket> {
+        Some(self)
+    }
+
+    fn status_flags(&self) -> StatusFlags {
+        // TODO: when we fully support O_ASYNC, return the flag
+        if self.is_nonblocking() {
+            StatusFlags::O_NONBLOCK
+        } else {
+            StatusFlags::empty()
+        }
+    }
+
+    fn set_status_flags(&self, new_flags: StatusFlags) -> Result<()> {
+        if new_flags.contains(StatusFlags::O_NONBLOCK) {
+            self.set_nonblocking(true);
+        } else {
+            self.set_nonblocking(false);
+        }
+        Ok(())
+    }
+
+    fn metadata(&self) -> Metadata {
+        // This is a dummy implementation.
+        // TODO: Add "SockFS" and link `NetlinkSocket` to it.
+        Metadata::new_socket(
+            0,
+            InodeMode::from_bits_truncate(0o140777),
+            aster_block::BLOCK_SIZE,
+        )
+    }
+}
+
+impl Socket for NetlinkSocket {
+    fn bind(&self, socket_addr: SocketAddr) -> Result<()> {
+        let addr = NetlinkSocketAddr::try_from(socket_addr)?;
+
+        let mut inner = self.inner.write();
+
+        match inner.bound_port.as_ref() {
+            Some(bound_port) if addr.port() != 0 && addr.port() != bound_port.port() => {
+                return_errno_with_message!(
+                    Errno::EINVAL,
+                    "the socket is already bound to a different port ID"
+                );
+            }
+            Some(_) => (),
+            None if addr.port() == 0 => {
+                inner.bound_port = Some(inner.port_table.bind_ephemeral()?);
+            }
+            None => {
+                inner.bound_port = Some(inner.port_table.bind(addr.port())?);
+            }
+        }
+
+        if addr.groups() != 0 {
+            // TODO: Support multicast.
+            warn!("joining multicast groups is not supported");
+        }
+        inner.groups = addr.groups();
+
+        Ok(())
+    }
+
+    fn connect(&self, socket_addr: SocketAddr) -> Result<()> {
+        let addr = NetlinkSocketAddr::try_from(socket_addr)?;
+
+        let mut inner = self.inner.write();
+        inner.bind_ephemeral()?;
+        inner.remote_addr = addr;
+
+        Ok(())
+    }
+
+    fn addr(&self) -> Result<SocketAddr> {
+        Ok(self.inner.read().local_addr().into())
+    }
+
+    fn peer_addr(&self) -> Result<SocketAddr> {
+        Ok(self.inner.read().remote_addr.into())
+    }
+
+    fn sendmsg(
+        &self,
+        reader: &mut dyn MultiRead,
+        message_header: MessageHeader,
+        flags: SendRecvFlags,
+    ) -> Result<usize> {
+        // TODO: Deal with flags
+        if !flags.is_all_supported() {
+            warn!("unsupported flags: {:?}", flags);
+        }
+
+        let MessageHeader {
+            addr,
+            control_messages,
+        } = message_header;
+
+        let remote_addr = match addr {
+            Some(remote_addr) => NetlinkSocketAddr::try_from(remote_addr)?,
+            None => self.inner.read().remote_addr,
+        };
+
+        if !control_messages.is_empty() {
+            // TODO: Support sending control message
+            warn!("sending control message is not supported");
+        }
+
+        self.try_send(reader, &remote_addr)
+    }
+
+    fn recvmsg(
+        &self,
+        writer: &mut dyn MultiWrite,
+        flags: SendRecvFlags,
+    ) -> Result<(usize, MessageHeader)> {
+        let supported_flags =
+            SendRecvFlags::MSG_PEEK | SendRecvFlags::MSG_TRUNC | SendRecvFlags::MSG_DONTWAIT;
+        if !supported_flags.contains(flags) {
+            warn!("unsupported flags: {:?}", flags - supported_flags);
+        }
+
+        let received_len = self.recv(writer, flags)?;
+
+        // All the messages are sent from the kernel.
+        let message_header =
+            MessageHeader::new(Some(NetlinkSocketAddr::new_kernel().into()), Vec::new());
+
+        Ok((received_len, message_header))
+    }
+
+    fn get_option(&self, option: &mut dyn SocketOption) -> Result<()> {
+        match_sock_option_mut!(option, {
+            socket_errors: SocketError => {
+                self.options.write().socket.get_and_clear_sock_errors(socket_errors);
+                return Ok(());
+            },
+            _ => ()
+        });
+
+        self.options.read().socket.get_option(option)
+    }
+
+    fn set_option(&self, option: &dyn SocketOption) -> Result<()> {
+        let mut options = self.options.write();
+        let mut inner = self.inner.write();
+
+        options.socket.set_option(option, &mut *inner)?;
+
+        Ok(())
+    }
+}
+
+impl SetSocketLevelOption for Inner {}
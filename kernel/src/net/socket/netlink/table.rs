@@ -0,0 +1,81 @@
+// SPDX-License-Identifier: MPL-2.0
+
+use alloc::collections::btree_set::BTreeSet;
+
+use super::addr::KERNEL_PORT;
+use crate::prelude::*;
+
+/// A table of the port IDs that have been bound to the netlink sockets of a protocol.
+pub(super) struct PortTable {
+    ports: Mutex<BTreeSet<u32>>,
+}
+
+/// The first port ID to try when allocating an ephemeral port ID.
+///
+/// Similar to Linux, if the process ID is in use, the negative numbers starting from -4096 are
+/// used as the ephemeral port IDs.
+const EPHEMERAL_PORT_START: i32 = -4096;
+
+impl PortTable {
+    pub(super) const fn new() -> Self {
+        Self {
+            ports: Mutex::new(BTreeSet::new()),
+        }
+    }
+
+    /// Binds the specified port ID.
+    pub(super) fn bind(&'static self, port: u32) -> Result<BoundPort> {
+        if port == KERNEL_PORT {
+            return_errno_with_message!(Errno::EINVAL, "the port ID of the kernel cannot be bound");
+        }
+
+        if !self.ports.lock().insert(port) {
+            return_errno_with_message!(Errno::EADDRINUSE, "the port ID is already in use");
+        }
+
+        Ok(BoundPort { port, table: self })
+    }
+
+    /// Binds an ephemeral port ID.
+    ///
+    /// The process ID of the current process is preferred if it is available.
+    pub(super) fn bind_ephemeral(&'static self) -> Result<BoundPort> {
+        let mut ports = self.ports.lock();
+
+        let pid = current!().pid();
+        let port = if !ports.contains(&pid) {
+            pid
+        } else {
+            (i32::MIN..=EPHEMERAL_PORT_START)
+                .rev()
+                .map(|port| port as u32)
+                .find(|port| !ports.contains(port))
+                .ok_or_else(|| {
+                    Error::with_message(Errno::EADDRINUSE, "no ephemeral port ID is available")
+                })?
+        };
+        ports.insert(port);
+
+        Ok(BoundPort { port, table: self })
+    }
+}
+
+/// A port ID that is bound to a netlink socket.
+///
+/// When dropped, the port ID is released.
+pub(super) struct BoundPort {
+    port: u32,
+    table: &'static PortTable,
+}
+
+impl BoundPort {
+    pub(super) fn port(&self) -> u32 {
+        self.port
+    }
+}
+
+impl Drop for BoundPort {
+    fn drop(&mut self) {
+        self.table.ports.lock().remove(&self.port);
+    }
+}
@@ -0,0 +1,53 @@
+// SPDX-License-Identifier: MPL-2.0
+
+use crate::{net::socket::SocketAddr, prelude::*};
+
+/// The socket address of a packet socket.
+///
+/// The socket address describes a link-layer endpoint, which is used to bind a packet socket to an
+/// iface, to specify the destination of a sent packet, or to describe the source of a received
+/// packet.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PacketSocketAddr {
+    /// The link-layer protocol in host byte order.
+    pub protocol: u16,
+    /// The index of the iface, where zero means any iface.
+    pub ifindex: u32,
+    /// The type of the hardware (e.g., `ARPHRD_ETHER`).
+    pub hardware_type: u16,
+    /// The type of the packet (e.g., `PACKET_HOST`).
+    pub packet_type: u8,
+    /// The length of the hardware address.
+    pub hardware_addr_len: u8,
+    /// The hardware address.
+    pub hardware_addr: [u8; 8],
+}
+
+impl PacketSocketAddr {
+    /// Returns the hardware address.
+    ///
+    /// This method returns `None` if the length of the hardware address is invalid.
+    pub fn hardware_addr(&self) -> Option<&[u8]> {
+        self.hardware_addr.get(..self.hardware_addr_len as usize)
+    }
+}
+
+impl TryFrom<SocketAddr> for PacketSocketAddr {
+    type Error = Error;
+
+    fn try_from(value: SocketAddr) -> Result<Self> {
+        match value {
+            SocketAddr::Packet(packet_addr) => Ok(packet_addr),
+            _ => return_errno_with_message!(
+                Errno::EINVAL,
+                "the socket address is not a valid packet socket address"
+            ),
+        }
+    }
+}
+
+impl From<PacketSocketAddr> for SocketAddr {
+    fn from(value: PacketSocketAddr) -> Self {
+        SocketAddr::Packet(value)
+    }
+}
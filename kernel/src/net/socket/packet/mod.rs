@@ -0,0 +1,43 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! Packet sockets.
+//!
+//! Packet sockets send and receive raw link-layer frames. A `SOCK_RAW` packet socket sees the
+//! whole frame including the link-layer header, while a `SOCK_DGRAM` packet socket sees only the
+//! payload and the link-layer header is described by the socket address.
+//!
+//! The frames are captured by [`PacketTap`], which is installed on the Ethernet ifaces.
+//!
+//! See <https://www.man7.org/linux/man-pages/man7/packet.7.html>.
+
+mod addr;
+mod socket;
+mod tap;
+
+pub use addr::PacketSocketAddr;
+pub use socket::PacketSocket;
+pub use tap::PacketTap;
+
+/// The protocol that matches every link-layer protocol.
+pub const ETH_P_ALL: u16 = 0x0003;
+
+/// The length of the Ethernet header.
+const ETH_HLEN: usize = 14;
+/// The length of the Ethernet address.
+const ETH_ALEN: usize = 6;
+
+/// The hardware type of the Ethernet ifaces.
+const ARPHRD_ETHER: u16 = 1;
+/// The hardware type of the loopback iface.
+const ARPHRD_LOOPBACK: u16 = 772;
+
+/// The packet is addressed to the local host.
+const PACKET_HOST: u8 = 0;
+/// The packet is a link-layer broadcast packet.
+const PACKET_BROADCAST: u8 = 1;
+/// The packet is a link-layer multicast packet.
+const PACKET_MULTICAST: u8 = 2;
+/// The packet is addressed to another host.
+const PACKET_OTHERHOST: u8 = 3;
+/// The packet is sent by the local host.
+const PACKET_OUTGOING: u8 = 4;
@@ -0,0 +1,402 @@
+// SPDX-License-Identifier: MPL-2.0
+
+use core::sync::atomic::{AtomicBool, Ordering};
+
+use aster_bigtcp::errors::SendFrameError;
+
+use super::{
+    tap::PacketReceiver, PacketSocketAddr, ARPHRD_ETHER, ARPHRD_LOOPBACK, ETH_ALEN, ETH_HLEN,
+};
+use crate::{
+    events::IoEvents,
+    fs::{
+        file_handle::FileLike,
+        utils::{InodeMode, Metadata, StatusFlags},
+    },
+    match_sock_option_mut, match_sock_option_ref,
+    net::{
+        iface::{get_iface_by_index, Iface},
+        socket::{
+            options::{AttachFilter, DetachFilter, Error as SocketError, SocketOption},
+            util::{
+                options::{SetSocketLevelOption, SocketOptionSet},
+                send_recv_flags::SendRecvFlags,
+                socket_addr::SocketAddr,
+                MessageHeader,
+            },
+            Socket,
+        },
+    },
+    prelude::*,
+    process::signal::{PollHandle, Pollable},
+    util::{MultiRead, MultiWrite},
+};
+
+#[derive(Debug, Clone)]
+struct OptionSet {
+    socket: SocketOptionSet,
+}
+
+impl OptionSet {
+    fn new() -> Self {
+        let socket = SocketOptionSet::new_packet();
+        OptionSet { socket }
+    }
+}
+
+/// A packet socket.
+pub struct PacketSocket {
+    options: RwLock<OptionSet>,
+    receiver: Arc<PacketReceiver>,
+    is_nonblocking: AtomicBool,
+}
+
+impl PacketSocket {
+    /// Creates a packet socket.
+    ///
+    /// The socket is a `SOCK_RAW` socket if `is_raw` is true, or a `SOCK_DGRAM` socket otherwise.
+    /// The protocol is in host byte order.
+    pub fn new(is_raw: bool, protocol: u16, is_nonblocking: bool) -> Arc<Self> {
+        let options = OptionSet::new();
+        let receiver =
+            PacketReceiver::new_registered(is_raw, protocol, options.socket.recv_buf() as usize);
+
+        Arc::new(Self {
+            options: RwLock::new(options),
+            receiver,
+            is_nonblocking: AtomicBool::new(is_nonblocking),
+        })
+    }
+
+    pub fn is_nonblocking(&self) -> bool {
+        self.is_nonblocking.load(Ordering::Relaxed)
+    }
+
+    pub fn set_nonblocking(&self, is_nonblocking: bool) {
+        self.is_nonblocking.store(is_nonblocking, Ordering::Relaxed);
+    }
+
+    fn try_send(
+        &self,
+        reader: &mut dyn MultiRead,
+        remote_addr: Option<&PacketSocketAddr>,
+    ) -> Result<usize> {
+        let (protocol, ifindex) = match remote_addr {
+            Some(remote_addr) => (remote_addr.protocol, remote_addr.ifindex),
+            None => {
+                let state = self.receiver.state();
+                (state.protocol(), state.ifindex())
+            }
+        };
+
+        if ifindex == 0 {
+            return_errno_with_message!(Errno::ENXIO, "the iface to send packets is not specified");
+        }
+        let Some(iface) = get_iface_by_index(ifindex) else {
+            return_errno_with_message!(Errno::ENXIO, "the iface to send packets does not exist");
+        };
+
+        let len = reader.sum_lens();
+        let mut frame = if self.receiver.is_raw() {
+            if len < ETH_HLEN {
+                return_errno_with_message!(Errno::EINVAL, "the frame is too short");
+            }
+            vec![0u8; len]
+        } else {
+            let Some(dst_addr) = remote_addr else {
+                return_errno_with_message!(
+                    Errno::EDESTADDRREQ,
+                    "the destination address is not specified"
+                );
+            };
+            let Some(dst_addr) = dst_addr
+                .hardware_addr()
+                .and_then(|addr| addr.get(..ETH_ALEN))
+            else {
+                return_errno_with_message!(Errno::EINVAL, "the hardware address is too short");
+            };
+
+            let mut frame = vec![0u8; ETH_HLEN + len];
+            frame[..ETH_ALEN].copy_from_slice(dst_addr);
+            if let Some(src_addr) = iface.hardware_addr() {
+                frame[ETH_ALEN..ETH_ALEN * 2].copy_from_slice(src_addr.as_bytes());
+            }
+            frame[ETH_ALEN * 2..ETH_HLEN].copy_from_slice(&protocol.to_be_bytes());
+            frame
+        };
+
+        let header_len = frame.len() - len;
+        let read_len = reader.read(&mut VmWriter::from(&mut frame[header_len..]))?;
+        frame.truncate(header_len + read_len);
+
+        match iface.send_frame(&frame) {
+            Ok(()) => Ok(read_len),
+            Err(SendFrameError::NotSupported) => {
+                // TODO: Support sending packets through the loopback iface.
+                return_errno_with_message!(
+                    Errno::EOPNOTSUPP,
+                    "the iface does not support sending packets"
+                )
+            }
+            Err(SendFrameError::TooLarge) => {
+                return_errno_with_message!(Errno::EMSGSIZE, "the packet is too large")
+            }
+            Err(SendFrameError::BufferFull) => {
+                return_errno_with_message!(Errno::ENOBUFS, "the device is busy")
+            }
+        }
+    }
+
+    fn try_recv(
+        &self,
+        writer: &mut dyn MultiWrite,
+        flags: SendRecvFlags,
+    ) -> Result<(usize, PacketSocketAddr)> {
+        let mut state = self.receiver.state();
+
+        let Some(packet) = state.front() else {
+            return_errno_with_message!(Errno::EAGAIN, "the receive queue is empty");
+        };
+
+        let copy_len = packet.data.len().min(writer.sum_lens());
+        writer.write(&mut VmReader::from(&packet.data[..copy_len]))?;
+
+        let len = if flags.contains(SendRecvFlags::MSG_TRUNC) {
+            packet.data.len()
+        } else {
+            copy_len
+        };
+        let addr = packet.addr;
+
+        // Similar to other datagram sockets, the rest of the packet is discarded if the buffer is
+        // too small, unless `MSG_PEEK` is specified.
+        if !flags.contains(SendRecvFlags::MSG_PEEK) {
+            state.pop_front();
+            self.receiver.pollee().invalidate();
+        }
+
+        Ok((len, addr))
+    }
+
+    fn recv(
+        &self,
+        writer: &mut dyn MultiWrite,
+        flags: SendRecvFlags,
+    ) -> Result<(usize, PacketSocketAddr)> {
+        if self.is_nonblocking() || flags.contains(SendRecvFlags::MSG_DONTWAIT) {
+            self.try_recv(writer, flags)
+        } else {
+            self.wait_events(IoEvents::IN, None, || self.try_recv(writer, flags))
+        }
+    }
+
+    fn check_io_events(&self) -> IoEvents {
+        let mut events = IoEvents::OUT;
+
+        if !self.receiver.state().is_empty() {
+            events |= IoEvents::IN;
+        }
+
+        events
+    }
+}
+
+impl Drop for PacketSocket {
+    fn drop(&mut self) {
+        self.receiver.unregister();
+    }
+}
+
+impl Pollable for PacketSocket {
+    fn poll(&self, mask: IoEvents, poller: Option<&mut PollHandle>) -> IoEvents {
+        self.receiver
+            .pollee()
+            .poll_with(mask, poller, || self.check_io_events())
+    }
+}
+
+impl FileLike for PacketSocket {
+    fn read(&self, writer: &mut VmWriter) -> Result<usize> {
+        // TODO: set correct flags
+        let flags = SendRecvFlags::empty();
+        self.recv(writer, flags).map(|(len, _)| len)
+    }
+
+    fn write(&self, reader: &mut VmReader) -> Result<usize> {
+        self.try_send(reader, None)
+    }
+
+    fn as_socket(&self) -> Option<&dyn Socket> {
+        Some(self)
+    }
+
+    fn status_flags(&self) -> StatusFlags {
+        // TODO: when we fully support O_ASYNC, return the flag
+        if self.is_nonblocking() {
+            StatusFlags::O_NONBLOCK
+        } else {
+            StatusFlags::empty()
+        }
+    }
+
+    fn set_status_flags(&self, new_flags: StatusFlags) -> Result<()> {
+        if new_flags.contains(StatusFlags::O_NONBLOCK) {
+            self.set_nonblocking(true);
+        } else {
+            self.set_nonblocking(false);
+        }
+        Ok(())
+    }
+
+    fn metadata(&self) -> Metadata {
+        // This is a dummy implementation.
+        // TODO: Add "SockFS" and link `PacketSocket` to it.
+        Metadata::new_socket(
+            0,
+            InodeMode::from_bits_truncate(0o140777),
+            aster_block::BLOCK_SIZE,
+        )
+    }
+}
+
+impl Socket for PacketSocket {
+    fn bind(&self, socket_addr: SocketAddr) -> Result<()> {
+        let addr = PacketSocketAddr::try_from(socket_addr)?;
+
+        if addr.ifindex != 0 && get_iface_by_index(addr.ifindex).is_none() {
+            return_errno_with_message!(Errno::ENODEV, "the iface to bind does not exist");
+        }
+
+        let mut state = self.receiver.state();
+        // Like Linux, a zero protocol keeps the protocol that the socket already has.
+        let protocol = if addr.protocol != 0 {
+            addr.protocol
+        } else {
+            state.protocol()
+        };
+        state.bind(protocol, addr.ifindex);
+
+        Ok(())
+    }
+
+    fn addr(&self) -> Result<SocketAddr> {
+        let (protocol, ifindex) = {
+            let state = self.receiver.state();
+            (state.protocol(), state.ifindex())
+        };
+
+        let mut addr = PacketSocketAddr {
+            protocol,
+            ifindex,
+            hardware_type: 0,
+            packet_type: 0,
+            hardware_addr_len: 0,
+            hardware_addr: [0; 8],
+        };
+        if let Some(iface) = get_iface_by_index(ifindex) {
+            fill_hardware_addr(&mut addr, iface.as_ref());
+        }
+
+        Ok(addr.into())
+    }
+
+    fn sendmsg(
+        &self,
+        reader: &mut dyn MultiRead,
+        message_header: MessageHeader,
+        flags: SendRecvFlags,
+    ) -> Result<usize> {
+        // TODO: Deal with flags
+        if !flags.is_all_supported() {
+            warn!("unsupported flags: {:?}", flags);
+        }
+
+        let MessageHeader {
+            addr,
+            control_messages,
+        } = message_header;
+
+        let remote_addr = addr.map(PacketSocketAddr::try_from).transpose()?;
+
+        if !control_messages.is_empty() {
+            // TODO: Support sending control message
+            warn!("sending control message is not supported");
+        }
+
+        self.try_send(reader, remote_addr.as_ref())
+    }
+
+    fn recvmsg(
+        &self,
+        writer: &mut dyn MultiWrite,
+        flags: SendRecvFlags,
+    ) -> Result<(usize, MessageHeader)> {
+        let supported_flags =
+            SendRecvFlags::MSG_PEEK | SendRecvFlags::MSG_TRUNC | SendRecvFlags::MSG_DONTWAIT;
+        if !supported_flags.contains(flags) {
+            warn!("unsupported flags: {:?}", flags - supported_flags);
+        }
+
+        let (received_len, addr) = self.recv(writer, flags)?;
+
+        let message_header = MessageHeader::new(Some(addr.into()), Vec::new());
+
+        Ok((received_len, message_header))
+    }
+
+    fn get_option(&self, option: &mut dyn SocketOption) -> Result<()> {
+        match_sock_option_mut!(option, {
+            socket_errors: SocketError => {
+                self.options.write().socket.get_and_clear_sock_errors(socket_errors);
+                return Ok(());
+            },
+            _ => ()
+        });
+
+        self.options.read().socket.get_option(option)
+    }
+
+    fn set_option(&self, option: &dyn SocketOption) -> Result<()> {
+        match_sock_option_ref!(option, {
+            attach_filter: AttachFilter => {
+                let filter = attach_filter.get().unwrap();
+                *self.receiver.state().filter_mut() = Some(filter.clone());
+                return Ok(());
+            },
+            _detach_filter: DetachFilter => {
+                if self.receiver.state().filter_mut().take().is_none() {
+                    return_errno_with_message!(Errno::ENOENT, "no filter is attached");
+                }
+                return Ok(());
+            },
+            _ => ()
+        });
+
+        let mut options = self.options.write();
+        options.socket.set_option(option, &mut PacketSocketOption)?;
+
+        let recv_buf = options.socket.recv_buf() as usize;
+        self.receiver.state().set_recv_buf(recv_buf);
+
+        Ok(())
+    }
+}
+
+/// Fills the hardware type and address of the iface into the socket address.
+fn fill_hardware_addr(addr: &mut PacketSocketAddr, iface: &Iface) {
+    addr.hardware_addr_len = ETH_ALEN as u8;
+
+    match iface.hardware_addr() {
+        Some(ether_addr) => {
+            addr.hardware_type = ARPHRD_ETHER;
+            addr.hardware_addr[..ETH_ALEN].copy_from_slice(ether_addr.as_bytes());
+        }
+        // Only the loopback iface has no hardware address. Like Linux, its hardware address
+        // consists of zeros.
+        None => addr.hardware_type = ARPHRD_LOOPBACK,
+    }
+}
+
+struct PacketSocketOption;
+
+impl SetSocketLevelOption for PacketSocketOption {}
@@ -0,0 +1,227 @@
+// SPDX-License-Identifier: MPL-2.0
+
+use alloc::collections::vec_deque::VecDeque;
+
+use aster_bigtcp::{
+    iface::{FrameDirection, TapFrame},
+    wire::EthernetAddress,
+};
+use ostd::sync::LocalIrqDisabled;
+
+use super::{
+    PacketSocketAddr, ARPHRD_ETHER, ETH_ALEN, ETH_HLEN, ETH_P_ALL, PACKET_BROADCAST, PACKET_HOST,
+    PACKET_MULTICAST, PACKET_OTHERHOST, PACKET_OUTGOING,
+};
+use crate::{
+    events::IoEvents,
+    net::socket::{util::filter::PacketMetadata, SocketFilter},
+    prelude::*,
+    process::signal::Pollee,
+};
+
+/// The receivers of all the packet sockets.
+///
+/// The lock disables local IRQs because frames are tapped while the ifaces are being polled,
+/// which may happen in the IRQ context.
+static RECEIVERS: SpinLock<Vec<Arc<PacketReceiver>>, LocalIrqDisabled> = SpinLock::new(Vec::new());
+
+/// A tap that delivers the link-layer frames of an Ethernet iface to the packet sockets.
+pub struct PacketTap {
+    ifindex: u32,
+    ether_addr: EthernetAddress,
+}
+
+impl PacketTap {
+    /// Creates a tap for the iface with the specified index and Ethernet address.
+    pub fn new(ifindex: u32, ether_addr: EthernetAddress) -> Self {
+        Self {
+            ifindex,
+            ether_addr,
+        }
+    }
+}
+
+impl TapFrame for PacketTap {
+    fn tap_frame(&self, frame: &[u8], direction: FrameDirection) {
+        let Some(header) = frame.get(..ETH_HLEN) else {
+            return;
+        };
+        let dst_addr = &header[..ETH_ALEN];
+        let src_addr = &header[ETH_ALEN..ETH_ALEN * 2];
+        let protocol = u16::from_be_bytes([header[ETH_ALEN * 2], header[ETH_ALEN * 2 + 1]]);
+
+        let packet_type = match direction {
+            FrameDirection::Outgoing => PACKET_OUTGOING,
+            FrameDirection::Incoming if dst_addr == [0xff; ETH_ALEN] => PACKET_BROADCAST,
+            FrameDirection::Incoming if dst_addr[0] & 1 != 0 => PACKET_MULTICAST,
+            FrameDirection::Incoming if dst_addr == self.ether_addr.as_bytes() => PACKET_HOST,
+            FrameDirection::Incoming => PACKET_OTHERHOST,
+        };
+
+        let metadata = PacketMetadata {
+            protocol,
+            packet_type,
+            ifindex: self.ifindex,
+        };
+
+        let mut hardware_addr = [0; 8];
+        hardware_addr[..ETH_ALEN].copy_from_slice(src_addr);
+        let addr = PacketSocketAddr {
+            protocol,
+            ifindex: self.ifindex,
+            hardware_type: ARPHRD_ETHER,
+            packet_type,
+            hardware_addr_len: ETH_ALEN as u8,
+            hardware_addr,
+        };
+
+        for receiver in RECEIVERS.lock().iter() {
+            receiver.receive(frame, &metadata, &addr);
+        }
+    }
+}
+
+/// The receiving side of a packet socket.
+pub(super) struct PacketReceiver {
+    is_raw: bool,
+    state: SpinLock<ReceiverState, LocalIrqDisabled>,
+    pollee: Pollee,
+}
+
+pub(super) struct ReceiverState {
+    /// The bound protocol in host byte order.
+    protocol: u16,
+    /// The bound iface index, where zero means any iface.
+    ifindex: u32,
+    filter: Option<SocketFilter>,
+    recv_buf: usize,
+    queue: VecDeque<ReceivedPacket>,
+    queued_bytes: usize,
+}
+
+/// A packet that is received by a packet socket.
+pub(super) struct ReceivedPacket {
+    pub(super) data: Vec<u8>,
+    pub(super) addr: PacketSocketAddr,
+}
+
+impl PacketReceiver {
+    /// Creates a receiver and registers it to receive frames from the ifaces.
+    pub(super) fn new_registered(is_raw: bool, protocol: u16, recv_buf: usize) -> Arc<Self> {
+        let state = ReceiverState {
+            protocol,
+            ifindex: 0,
+            filter: None,
+            recv_buf,
+            queue: VecDeque::new(),
+            queued_bytes: 0,
+        };
+
+        let receiver = Arc::new(Self {
+            is_raw,
+            state: SpinLock::new(state),
+            pollee: Pollee::new(),
+        });
+        RECEIVERS.lock().push(receiver.clone());
+
+        receiver
+    }
+
+    /// Unregisters the receiver so that it no longer receives frames.
+    pub(super) fn unregister(self: &Arc<Self>) {
+        RECEIVERS
+            .lock()
+            .retain(|receiver| !Arc::ptr_eq(receiver, self));
+    }
+
+    pub(super) fn is_raw(&self) -> bool {
+        self.is_raw
+    }
+
+    pub(super) fn state(&self) -> SpinLockGuard<'_, ReceiverState, LocalIrqDisabled> {
+        self.state.lock()
+    }
+
+    pub(super) fn pollee(&self) -> &Pollee {
+        &self.pollee
+    }
+
+    fn receive(&self, frame: &[u8], metadata: &PacketMetadata, addr: &PacketSocketAddr) {
+        let mut state = self.state.lock();
+
+        let is_outgoing = metadata.packet_type == PACKET_OUTGOING;
+        // Similar to Linux, the outgoing frames are delivered only to the sockets that receive
+        // all the protocols.
+        let protocol_matches = state.protocol == ETH_P_ALL
+            || (!is_outgoing && state.protocol != 0 && state.protocol == metadata.protocol);
+        if !protocol_matches || (state.ifindex != 0 && state.ifindex != metadata.ifindex) {
+            return;
+        }
+
+        let packet = if self.is_raw {
+            frame
+        } else {
+            &frame[ETH_HLEN..]
+        };
+
+        let len = match state.filter.as_ref() {
+            Some(filter) => match filter.run(packet, metadata) as usize {
+                0 => return,
+                len => len.min(packet.len()),
+            },
+            None => packet.len(),
+        };
+
+        // Like Linux, the packet is dropped only if the receive buffer is already full, so a
+        // packet can always be received if the queue is empty.
+        if state.queued_bytes >= state.recv_buf {
+            return;
+        }
+
+        state.queue.push_back(ReceivedPacket {
+            data: packet[..len].to_vec(),
+            addr: *addr,
+        });
+        state.queued_bytes += len;
+        drop(state);
+
+        self.pollee.notify(IoEvents::IN);
+    }
+}
+
+impl ReceiverState {
+    pub(super) fn protocol(&self) -> u16 {
+        self.protocol
+    }
+
+    pub(super) fn ifindex(&self) -> u32 {
+        self.ifindex
+    }
+
+    pub(super) fn bind(&mut self, protocol: u16, ifindex: u32) {
+        self.protocol = protocol;
+        self.ifindex = ifindex;
+    }
+
+    pub(super) fn filter_mut(&mut self) -> &mut Option<SocketFilter> {
+        &mut self.filter
+    }
+
+    pub(super) fn set_recv_buf(&mut self, recv_buf: usize) {
+        self.recv_buf = recv_buf;
+    }
+
+    pub(super) fn front(&self) -> Option<&ReceivedPacket> {
+        self.queue.front()
+    }
+
+    pub(super) fn pop_front(&mut self) {
+        if let Some(packet) = self.queue.pop_front() {
+            self.queued_bytes -= packet.data.len();
+        }
+    }
+
+    pub(super) fn is_empty(&self) -> bool {
+        self.queue.is_empty()
+    }
+}
@@ -1,5 +1,7 @@
 // SPDX-License-Identifier: MPL-2.0
 
+use ostd::task::Task;
+
 use crate::{
     fs::{
         fs_resolver::{split_path, FsPath},
@@ -7,14 +9,14 @@ use crate::{
         utils::{InodeMode, InodeType, Permission},
     },
     prelude::*,
-    process::posix_thread::AsPosixThread,
+    process::posix_thread::AsThreadLocal,
 };
 
 pub fn lookup_socket_file(path: &str) -> Result<Dentry> {
     let dentry = {
-        let current = current_thread!();
-        let current = current.as_posix_thread().unwrap();
-        let fs = current.fs().resolver().read();
+        let current = Task::current().unwrap();
+        let fs_ref = current.as_thread_local().unwrap().borrow_fs();
+        let fs = fs_ref.resolver().read();
         let fs_path = FsPath::try_from(path)?;
         fs.lookup(&fs_path)?
     };
@@ -41,9 +43,9 @@ pub fn create_socket_file(path: &str) -> Result<Dentry> {
     let (parent_pathname, file_name) = split_path(path);
 
     let parent = {
-        let current = current_thread!();
-        let current = current.as_posix_thread().unwrap();
-        let fs = current.fs().resolver().read();
+        let current = Task::current().unwrap();
+        let fs_ref = current.as_thread_local().unwrap().borrow_fs();
+        let fs = fs_ref.resolver().read();
         let parent_path = FsPath::try_from(parent_pathname)?;
         fs.lookup(&parent_path)?
     };
@@ -6,10 +6,10 @@ use ostd::sync::PreemptDisabled;
 
 use crate::{
     events::IoEvents,
-    fs::utils::{Channel, Consumer, Producer},
+    fs::utils::{AuxData, Channel, Consumer, Producer},
     net::socket::{
         unix::{addr::UnixSocketAddrBound, UnixSocketAddr},
-        SockShutdownCmd,
+        SockShutdownCmd, UCred,
     },
     prelude::*,
     process::signal::{PollHandle, Pollee},
@@ -18,6 +18,8 @@ use crate::{
 
 pub(super) struct Connected {
     addr: AddrView,
+    /// The credentials of the peer when the connection is established.
+    peer_cred: UCred,
     reader: Consumer<u8>,
     writer: Producer<u8>,
 }
@@ -26,6 +28,8 @@ impl Connected {
     pub(super) fn new_pair(
         addr: Option<UnixSocketAddrBound>,
         peer_addr: Option<UnixSocketAddrBound>,
+        cred: UCred,
+        peer_cred: UCred,
         reader_pollee: Option<Pollee>,
         writer_pollee: Option<Pollee>,
     ) -> (Connected, Connected) {
@@ -38,11 +42,13 @@ impl Connected {
 
         let this = Connected {
             addr: addr_this,
+            peer_cred,
             reader: reader_this,
             writer: writer_this,
         };
         let peer = Connected {
             addr: addr_peer,
+            peer_cred: cred,
             reader: reader_peer,
             writer: writer_peer,
         };
@@ -58,6 +64,10 @@ impl Connected {
         self.addr.peer_addr()
     }
 
+    pub(super) fn peer_cred(&self) -> UCred {
+        self.peer_cred
+    }
+
     pub(super) fn bind(&self, addr_to_bind: UnixSocketAddr) -> Result<()> {
         let mut addr = self.addr.addr();
 
@@ -71,12 +81,24 @@ impl Connected {
         Ok(())
     }
 
-    pub(super) fn try_read(&self, writer: &mut dyn MultiWrite) -> Result<usize> {
-        self.reader.try_read(writer)
+    pub(super) fn try_read(&self, writer: &mut dyn MultiWrite) -> Result<(usize, Option<AuxData>)> {
+        self.reader.try_read_with_aux(writer)
     }
 
-    pub(super) fn try_write(&self, reader: &mut dyn MultiRead) -> Result<usize> {
-        self.writer.try_write(reader)
+    /// Tries to write the data with the auxiliary data attached to it.
+    ///
+    /// If `is_seqpacket` is true, the data is written as a message whose boundary is preserved.
+    pub(super) fn try_write(
+        &self,
+        reader: &mut dyn MultiRead,
+        aux: &mut Option<AuxData>,
+        is_seqpacket: bool,
+    ) -> Result<usize> {
+        if is_seqpacket {
+            self.writer.try_write_message(reader, aux)
+        } else {
+            self.writer.try_write_with_aux(reader, aux)
+        }
     }
 
     pub(super) fn shutdown(&self, cmd: SockShutdownCmd) {
@@ -10,7 +10,7 @@ use crate::{
     events::IoEvents,
     net::socket::{
         unix::addr::{UnixSocketAddr, UnixSocketAddrBound},
-        SockShutdownCmd,
+        SockShutdownCmd, UCred,
     },
     prelude::*,
     process::signal::{PollHandle, Pollee},
@@ -46,7 +46,11 @@ impl Init {
         Ok(())
     }
 
-    pub(super) fn into_connected(self, peer_addr: UnixSocketAddrBound) -> (Connected, Connected) {
+    pub(super) fn into_connected(
+        self,
+        peer_addr: UnixSocketAddrBound,
+        peer_cred: UCred,
+    ) -> (Connected, Connected) {
         let Init {
             addr,
             reader_pollee,
@@ -58,6 +62,8 @@ impl Init {
         let (this_conn, peer_conn) = Connected::new_pair(
             addr,
             Some(peer_addr),
+            UCred::new_current(),
+            peer_cred,
             Some(reader_pollee),
             Some(writer_pollee),
         );
@@ -73,7 +79,11 @@ impl Init {
         (this_conn, peer_conn)
     }
 
-    pub(super) fn listen(self, backlog: usize) -> core::result::Result<Listener, (Error, Self)> {
+    pub(super) fn listen(
+        self,
+        backlog: usize,
+        is_seqpacket: bool,
+    ) -> core::result::Result<Listener, (Error, Self)> {
         let Some(addr) = self.addr else {
             return Err((
                 Error::with_message(Errno::EINVAL, "the socket is not bound"),
@@ -86,6 +96,7 @@ impl Init {
             self.reader_pollee,
             self.writer_pollee,
             backlog,
+            is_seqpacket,
             self.is_read_shutdown.into_inner(),
             self.is_write_shutdown.into_inner(),
         ))
@@ -14,7 +14,7 @@ use crate::{
     fs::file_handle::FileLike,
     net::socket::{
         unix::addr::{UnixSocketAddrBound, UnixSocketAddrKey},
-        SockShutdownCmd, SocketAddr,
+        SockShutdownCmd, SocketAddr, UCred,
     },
     prelude::*,
     process::signal::{PollHandle, Pollee},
@@ -32,11 +32,12 @@ impl Listener {
         reader_pollee: Pollee,
         writer_pollee: Pollee,
         backlog: usize,
+        is_seqpacket: bool,
         is_read_shutdown: bool,
         is_write_shutdown: bool,
     ) -> Self {
         let backlog = BACKLOG_TABLE
-            .add_backlog(addr, reader_pollee, backlog, is_read_shutdown)
+            .add_backlog(addr, reader_pollee, backlog, is_seqpacket, is_read_shutdown)
             .unwrap();
         writer_pollee.invalidate();
 
@@ -51,11 +52,15 @@ impl Listener {
         self.backlog.addr()
     }
 
+    pub(super) fn cred(&self) -> UCred {
+        self.backlog.cred
+    }
+
     pub(super) fn try_accept(&self) -> Result<(Arc<dyn FileLike>, SocketAddr)> {
         let connected = self.backlog.pop_incoming()?;
         let peer_addr = connected.peer_addr().into();
 
-        let socket = UnixStreamSocket::new_connected(connected, false);
+        let socket = UnixStreamSocket::new_connected(connected, false, self.backlog.is_seqpacket());
         Ok((socket, peer_addr))
     }
 
@@ -121,6 +126,7 @@ impl BacklogTable {
         addr: UnixSocketAddrBound,
         pollee: Pollee,
         backlog: usize,
+        is_seqpacket: bool,
         is_shutdown: bool,
     ) -> Option<Arc<Backlog>> {
         let addr_key = addr.to_key();
@@ -133,7 +139,13 @@ impl BacklogTable {
 
         // Note that the cached events can be correctly inherited from `Init`, so there is no need
         // to explicitly call `Pollee::invalidate`.
-        let new_backlog = Arc::new(Backlog::new(addr, pollee, backlog, is_shutdown));
+        let new_backlog = Arc::new(Backlog::new(
+            addr,
+            pollee,
+            backlog,
+            is_seqpacket,
+            is_shutdown,
+        ));
         backlog_sockets.insert(addr_key, new_backlog.clone());
 
         Some(new_backlog)
@@ -150,6 +162,9 @@ impl BacklogTable {
 
 pub(super) struct Backlog {
     addr: UnixSocketAddrBound,
+    /// The credentials of the listening socket when `listen` is called.
+    cred: UCred,
+    is_seqpacket: bool,
     pollee: Pollee,
     backlog: AtomicUsize,
     incoming_conns: SpinLock<Option<VecDeque<Connected>>>,
@@ -157,7 +172,13 @@ pub(super) struct Backlog {
 }
 
 impl Backlog {
-    fn new(addr: UnixSocketAddrBound, pollee: Pollee, backlog: usize, is_shutdown: bool) -> Self {
+    fn new(
+        addr: UnixSocketAddrBound,
+        pollee: Pollee,
+        backlog: usize,
+        is_seqpacket: bool,
+        is_shutdown: bool,
+    ) -> Self {
         let incoming_sockets = if is_shutdown {
             None
         } else {
@@ -166,6 +187,8 @@ impl Backlog {
 
         Self {
             addr,
+            cred: UCred::new_current(),
+            is_seqpacket,
             pollee,
             backlog: AtomicUsize::new(backlog),
             incoming_conns: SpinLock::new(incoming_sockets),
@@ -177,6 +200,10 @@ impl Backlog {
         &self.addr
     }
 
+    pub(super) fn is_seqpacket(&self) -> bool {
+        self.is_seqpacket
+    }
+
     fn pop_incoming(&self) -> Result<Connected> {
         let mut locked_incoming_conns = self.incoming_conns.lock();
 
@@ -260,7 +287,7 @@ impl Backlog {
             ));
         }
 
-        let (client_conn, server_conn) = init.into_connected(self.addr.clone());
+        let (client_conn, server_conn) = init.into_connected(self.addr.clone(), self.cred);
 
         incoming_conns.push_back(server_conn);
         self.pollee.notify(IoEvents::IN);
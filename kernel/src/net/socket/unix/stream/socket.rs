@@ -13,35 +13,48 @@ use crate::{
     events::IoEvents,
     fs::{
         file_handle::FileLike,
-        utils::{InodeMode, Metadata, StatusFlags},
+        utils::{AuxData, InodeMode, Metadata, StatusFlags},
     },
+    match_sock_option_mut,
     net::socket::{
+        options::{PeerCred, SocketOption},
         unix::UnixSocketAddr,
         util::{send_recv_flags::SendRecvFlags, socket_addr::SocketAddr, MessageHeader},
-        SockShutdownCmd, Socket,
+        ControlMessage, SockShutdownCmd, Socket, UCred,
     },
     prelude::*,
     process::signal::{PollHandle, Pollable},
     util::{MultiRead, MultiWrite},
 };
 
+/// A UNIX domain socket of the `SOCK_STREAM` or `SOCK_SEQPACKET` type.
+///
+/// The two types share the same connection-oriented implementation. The only difference is that
+/// the boundaries of the messages sent via a `SOCK_SEQPACKET` socket are preserved.
 pub struct UnixStreamSocket {
     state: RwMutex<Takeable<State>>,
     is_nonblocking: AtomicBool,
+    is_seqpacket: bool,
 }
 
 impl UnixStreamSocket {
-    pub(super) fn new_init(init: Init, is_nonblocking: bool) -> Arc<Self> {
+    pub(super) fn new_init(init: Init, is_nonblocking: bool, is_seqpacket: bool) -> Arc<Self> {
         Arc::new(Self {
             state: RwMutex::new(Takeable::new(State::Init(init))),
             is_nonblocking: AtomicBool::new(is_nonblocking),
+            is_seqpacket,
         })
     }
 
-    pub(super) fn new_connected(connected: Connected, is_nonblocking: bool) -> Arc<Self> {
+    pub(super) fn new_connected(
+        connected: Connected,
+        is_nonblocking: bool,
+        is_seqpacket: bool,
+    ) -> Arc<Self> {
         Arc::new(Self {
             state: RwMutex::new(Takeable::new(State::Connected(connected))),
             is_nonblocking: AtomicBool::new(is_nonblocking),
+            is_seqpacket,
         })
     }
 }
@@ -53,36 +66,51 @@ enum State {
 }
 
 impl UnixStreamSocket {
-    pub fn new(is_nonblocking: bool) -> Arc<Self> {
-        Self::new_init(Init::new(), is_nonblocking)
+    pub fn new(is_nonblocking: bool, is_seqpacket: bool) -> Arc<Self> {
+        Self::new_init(Init::new(), is_nonblocking, is_seqpacket)
     }
 
-    pub fn new_pair(is_nonblocking: bool) -> (Arc<Self>, Arc<Self>) {
-        let (conn_a, conn_b) = Connected::new_pair(None, None, None, None);
+    pub fn new_pair(is_nonblocking: bool, is_seqpacket: bool) -> (Arc<Self>, Arc<Self>) {
+        let cred = UCred::new_current();
+        let (conn_a, conn_b) = Connected::new_pair(None, None, cred, cred, None, None);
         (
-            Self::new_connected(conn_a, is_nonblocking),
-            Self::new_connected(conn_b, is_nonblocking),
+            Self::new_connected(conn_a, is_nonblocking, is_seqpacket),
+            Self::new_connected(conn_b, is_nonblocking, is_seqpacket),
         )
     }
 
-    fn send(&self, reader: &mut dyn MultiRead, flags: SendRecvFlags) -> Result<usize> {
+    fn send(
+        &self,
+        reader: &mut dyn MultiRead,
+        aux: &mut Option<AuxData>,
+        flags: SendRecvFlags,
+    ) -> Result<usize> {
         if self.is_nonblocking() {
-            self.try_send(reader, flags)
+            self.try_send(reader, aux, flags)
         } else {
-            self.wait_events(IoEvents::OUT, None, || self.try_send(reader, flags))
+            self.wait_events(IoEvents::OUT, None, || self.try_send(reader, aux, flags))
         }
     }
 
-    fn try_send(&self, buf: &mut dyn MultiRead, _flags: SendRecvFlags) -> Result<usize> {
+    fn try_send(
+        &self,
+        buf: &mut dyn MultiRead,
+        aux: &mut Option<AuxData>,
+        _flags: SendRecvFlags,
+    ) -> Result<usize> {
         match self.state.read().as_ref() {
-            State::Connected(connected) => connected.try_write(buf),
+            State::Connected(connected) => connected.try_write(buf, aux, self.is_seqpacket),
             State::Init(_) | State::Listen(_) => {
                 return_errno_with_message!(Errno::ENOTCONN, "the socket is not connected")
             }
         }
     }
 
-    fn recv(&self, writer: &mut dyn MultiWrite, flags: SendRecvFlags) -> Result<usize> {
+    fn recv(
+        &self,
+        writer: &mut dyn MultiWrite,
+        flags: SendRecvFlags,
+    ) -> Result<(usize, Option<AuxData>)> {
         if self.is_nonblocking() {
             self.try_recv(writer, flags)
         } else {
@@ -90,7 +118,11 @@ impl UnixStreamSocket {
         }
     }
 
-    fn try_recv(&self, buf: &mut dyn MultiWrite, _flags: SendRecvFlags) -> Result<usize> {
+    fn try_recv(
+        &self,
+        buf: &mut dyn MultiWrite,
+        _flags: SendRecvFlags,
+    ) -> Result<(usize, Option<AuxData>)> {
         match self.state.read().as_ref() {
             State::Connected(connected) => connected.try_read(buf),
             State::Init(_) | State::Listen(_) => {
@@ -171,14 +203,15 @@ impl FileLike for UnixStreamSocket {
     fn read(&self, writer: &mut VmWriter) -> Result<usize> {
         // TODO: Set correct flags
         let flags = SendRecvFlags::empty();
-        let read_len = self.recv(writer, flags)?;
+        // The auxiliary data (e.g., the passed files) is discarded, as in Linux.
+        let (read_len, _) = self.recv(writer, flags)?;
         Ok(read_len)
     }
 
     fn write(&self, reader: &mut VmReader) -> Result<usize> {
         // TODO: Set correct flags
         let flags = SendRecvFlags::empty();
-        self.send(reader, flags)
+        self.send(reader, &mut None, flags)
     }
 
     fn status_flags(&self) -> StatusFlags {
@@ -222,6 +255,12 @@ impl Socket for UnixStreamSocket {
     fn connect(&self, socket_addr: SocketAddr) -> Result<()> {
         let remote_addr = UnixSocketAddr::try_from(socket_addr)?.connect()?;
         let backlog = get_backlog(&remote_addr)?;
+        if backlog.is_seqpacket() != self.is_seqpacket {
+            return_errno_with_message!(
+                Errno::EPROTOTYPE,
+                "the listening socket is of a different type"
+            );
+        }
 
         if self.is_nonblocking() {
             self.try_connect(&backlog)
@@ -258,7 +297,7 @@ impl Socket for UnixStreamSocket {
                 }
             };
 
-            let listener = match init.listen(backlog) {
+            let listener = match init.listen(backlog, self.is_seqpacket) {
                 Ok(listener) => listener,
                 Err((err, init)) => {
                     return (State::Init(init), Err(err));
@@ -308,6 +347,23 @@ impl Socket for UnixStreamSocket {
         Ok(peer_addr.into())
     }
 
+    fn get_option(&self, option: &mut dyn SocketOption) -> Result<()> {
+        match_sock_option_mut!(option, {
+            socket_peer_cred: PeerCred => {
+                let peer_cred = match self.state.read().as_ref() {
+                    State::Connected(connected) => connected.peer_cred(),
+                    // Linux reports the credentials of the listening socket itself.
+                    State::Listen(listener) => listener.cred(),
+                    State::Init(_) => UCred::new_invalid(),
+                };
+                socket_peer_cred.set(peer_cred);
+            },
+            _ => return_errno_with_message!(Errno::ENOPROTOOPT, "the socket option to get is unknown")
+        });
+
+        Ok(())
+    }
+
     fn sendmsg(
         &self,
         reader: &mut dyn MultiRead,
@@ -320,15 +376,28 @@ impl Socket for UnixStreamSocket {
         }
 
         let MessageHeader {
-            control_message, ..
+            control_messages, ..
         } = message_header;
 
-        if control_message.is_some() {
-            // TODO: Support sending control message
-            warn!("sending control message is not supported");
+        let mut files = Vec::new();
+        for control_message in control_messages {
+            match control_message {
+                ControlMessage::Files(new_files) => files.extend(new_files),
+                ControlMessage::Credentials(_) => {
+                    // TODO: Support `SO_PASSCRED` so that the credentials can be received.
+                    warn!("passing credentials is not supported");
+                }
+                // These control messages are only reported for received IP packets.
+                ControlMessage::Ttl(_) | ControlMessage::HopLimit(_) => (),
+            }
         }
 
-        self.send(reader, flags)
+        let mut aux = if files.is_empty() {
+            None
+        } else {
+            Some(Box::new(files) as AuxData)
+        };
+        self.send(reader, &mut aux, flags)
     }
 
     fn recvmsg(
@@ -341,11 +410,17 @@ impl Socket for UnixStreamSocket {
             warn!("unsupported flags: {:?}", flags);
         }
 
-        let received_bytes = self.recv(writer, flags)?;
+        let (received_bytes, aux) = self.recv(writer, flags)?;
 
-        // TODO: Receive control message
+        let control_messages = match aux {
+            Some(aux) => {
+                let files = aux.downcast::<Vec<Arc<dyn FileLike>>>().unwrap();
+                vec![ControlMessage::Files(*files)]
+            }
+            None => Vec::new(),
+        };
 
-        let message_header = MessageHeader::new(None, None);
+        let message_header = MessageHeader::new(None, control_messages);
 
         Ok((received_bytes, message_header))
     }
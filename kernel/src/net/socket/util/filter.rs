@@ -0,0 +1,211 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! Socket filters in the classic BPF format.
+//!
+//! A socket filter is a program attached to a socket with the `SO_ATTACH_FILTER` option. The
+//! program is run against every incoming packet. It returns the number of bytes of the packet to
+//! keep, where zero means that the packet should be dropped.
+//!
+//! For more details, see <https://www.kernel.org/doc/html/v6.0/networking/filter.html>.
+
+use crate::{
+    prelude::*,
+    util::bpf::{BpfInput, BpfProgram, CSockFilter, BPF_ABS, BPF_LD, BPF_W},
+};
+
+// Offsets of the ancillary data.
+const SKF_AD_OFF: u32 = -0x1000i32 as u32;
+const SKF_AD_PROTOCOL: u32 = 0;
+const SKF_AD_PKTTYPE: u32 = 4;
+const SKF_AD_IFINDEX: u32 = 8;
+
+/// A validated socket filter program.
+#[derive(Debug, Clone)]
+pub struct SocketFilter {
+    program: BpfProgram,
+}
+
+/// The metadata of a packet, which can be loaded by the filter as the ancillary data.
+#[derive(Debug, Clone, Copy)]
+pub struct PacketMetadata {
+    /// The link-layer protocol in host byte order.
+    pub protocol: u16,
+    /// The packet type (e.g., `PACKET_HOST`).
+    pub packet_type: u8,
+    /// The index of the iface.
+    pub ifindex: u32,
+}
+
+impl SocketFilter {
+    /// The maximum number of instructions in a filter program.
+    pub const MAX_LEN: usize = BpfProgram::MAX_LEN;
+
+    /// Creates a socket filter from the instructions.
+    ///
+    /// This method fails with `EINVAL` if the program is invalid. See [`BpfProgram::new`] for
+    /// the requirements of a valid program. In addition, the program must only load known
+    /// ancillary data.
+    pub fn new(insns: Vec<CSockFilter>) -> Result<Self> {
+        let program = BpfProgram::new(insns, |insn| {
+            insn.code != BPF_LD | BPF_W | BPF_ABS
+                || insn.k < SKF_AD_OFF
+                || matches!(
+                    insn.k - SKF_AD_OFF,
+                    SKF_AD_PROTOCOL | SKF_AD_PKTTYPE | SKF_AD_IFINDEX
+                )
+        })?;
+
+        Ok(Self { program })
+    }
+
+    /// Runs the filter against the packet.
+    ///
+    /// This method returns the number of bytes of the packet to keep.
+    pub fn run(&self, packet: &[u8], metadata: &PacketMetadata) -> u32 {
+        self.program.run(&Packet { packet, metadata })
+    }
+}
+
+/// A packet with its metadata, which is the input data of a socket filter.
+struct Packet<'a> {
+    packet: &'a [u8],
+    metadata: &'a PacketMetadata,
+}
+
+impl BpfInput for Packet<'_> {
+    fn data_len(&self) -> u32 {
+        self.packet.len() as u32
+    }
+
+    /// Loads a big-endian value of the specific size from the packet.
+    fn load(&self, offset: u32, size: usize) -> Option<u32> {
+        let offset = offset as usize;
+        let bytes = self.packet.get(offset..offset.checked_add(size)?)?;
+
+        let val = match size {
+            4 => u32::from_be_bytes(bytes.try_into().ok()?),
+            2 => u16::from_be_bytes(bytes.try_into().ok()?) as u32,
+            _ => bytes[0] as u32,
+        };
+
+        Some(val)
+    }
+
+    fn load_abs(&self, offset: u32, size: usize) -> Option<u32> {
+        if offset < SKF_AD_OFF || size != 4 {
+            return self.load(offset, size);
+        }
+
+        let val = match offset - SKF_AD_OFF {
+            SKF_AD_PROTOCOL => self.metadata.protocol as u32,
+            SKF_AD_PKTTYPE => self.metadata.packet_type as u32,
+            SKF_AD_IFINDEX => self.metadata.ifindex,
+            _ => unreachable!("the ancillary data should have been validated"),
+        };
+
+        Some(val)
+    }
+}
+
+#[cfg(ktest)]
+mod test {
+    use ostd::prelude::*;
+
+    use super::*;
+    use crate::util::bpf::{
+        BPF_A, BPF_ALU, BPF_DIV, BPF_IMM, BPF_K, BPF_LDX, BPF_LEN, BPF_MEM, BPF_MUL, BPF_RET,
+        BPF_ST, BPF_SUB, BPF_X,
+    };
+
+    const fn insn(code: u16, jt: u8, jf: u8, k: u32) -> CSockFilter {
+        CSockFilter { code, jt, jf, k }
+    }
+
+    const METADATA: PacketMetadata = PacketMetadata {
+        protocol: 0x0800,
+        packet_type: 0,
+        ifindex: 1,
+    };
+
+    /// Returns the program generated by `tcpdump -dd arp`.
+    fn arp_program() -> Vec<CSockFilter> {
+        vec![
+            insn(0x28, 0, 0, 0x0000000c),
+            insn(0x15, 0, 1, 0x00000806),
+            insn(0x06, 0, 0, 0x00040000),
+            insn(0x06, 0, 0, 0x00000000),
+        ]
+    }
+
+    #[ktest]
+    fn test_run() {
+        let filter = SocketFilter::new(arp_program()).unwrap();
+
+        let mut frame = [0u8; 42];
+        frame[12..14].copy_from_slice(&[0x08, 0x06]);
+        assert_eq!(filter.run(&frame, &METADATA), 0x40000);
+
+        frame[12..14].copy_from_slice(&[0x08, 0x00]);
+        assert_eq!(filter.run(&frame, &METADATA), 0);
+
+        // Out-of-bounds loads drop the packet.
+        assert_eq!(filter.run(&frame[..13], &METADATA), 0);
+    }
+
+    #[ktest]
+    fn test_alu_and_memory() {
+        let program = vec![
+            // A = len; M[3] = A; X = 2; A = M[3] * X; A -= 1; ret A
+            insn(BPF_LD | BPF_W | BPF_LEN, 0, 0, 0),
+            insn(BPF_ST, 0, 0, 3),
+            insn(BPF_LDX | BPF_W | BPF_IMM, 0, 0, 2),
+            insn(BPF_LD | BPF_W | BPF_MEM, 0, 0, 3),
+            insn(BPF_ALU | BPF_MUL | BPF_X, 0, 0, 0),
+            insn(BPF_ALU | BPF_SUB | BPF_K, 0, 0, 1),
+            insn(BPF_RET | BPF_A, 0, 0, 0),
+        ];
+        let filter = SocketFilter::new(program).unwrap();
+        assert_eq!(filter.run(&[0u8; 10], &METADATA), 19);
+
+        let program = vec![
+            insn(BPF_LD | BPF_W | BPF_ABS, 0, 0, SKF_AD_OFF + SKF_AD_PROTOCOL),
+            insn(BPF_RET | BPF_A, 0, 0, 0),
+        ];
+        let filter = SocketFilter::new(program).unwrap();
+        assert_eq!(filter.run(&[], &METADATA), 0x0800);
+    }
+
+    #[ktest]
+    fn test_invalid() {
+        // Empty programs
+        assert!(SocketFilter::new(Vec::new()).is_err());
+
+        // Programs that do not end with a return instruction
+        let mut program = arp_program();
+        program.pop();
+        assert!(SocketFilter::new(program).is_err());
+
+        // Jumps out of the program
+        let mut program = arp_program();
+        program[1].jf = 2;
+        assert!(SocketFilter::new(program).is_err());
+
+        // Division by zero
+        let program = vec![
+            insn(BPF_ALU | BPF_DIV | BPF_K, 0, 0, 0),
+            insn(BPF_RET | BPF_A, 0, 0, 0),
+        ];
+        assert!(SocketFilter::new(program).is_err());
+
+        // Out-of-bounds memory accesses
+        let program = vec![insn(BPF_ST, 0, 0, 16), insn(BPF_RET | BPF_A, 0, 0, 0)];
+        assert!(SocketFilter::new(program).is_err());
+
+        // Unknown ancillary data
+        let program = vec![
+            insn(BPF_LD | BPF_W | BPF_ABS, 0, 0, SKF_AD_OFF + 0x100),
+            insn(BPF_RET | BPF_A, 0, 0, 0),
+        ];
+        assert!(SocketFilter::new(program).is_err());
+    }
+}
@@ -1,21 +1,27 @@
 // SPDX-License-Identifier: MPL-2.0
 
+use core::fmt;
+
 use super::socket_addr::SocketAddr;
-use crate::prelude::*;
+use crate::{
+    fs::file_handle::FileLike,
+    prelude::*,
+    process::{posix_thread::AsPosixThread, Gid, Pid, Uid},
+};
 
 /// Message header used for sendmsg/recvmsg.
 #[derive(Debug)]
 pub struct MessageHeader {
     pub(in crate::net) addr: Option<SocketAddr>,
-    pub(in crate::net) control_message: Option<ControlMessage>,
+    pub(in crate::net) control_messages: Vec<ControlMessage>,
 }
 
 impl MessageHeader {
     /// Creates a new `MessageHeader`.
-    pub const fn new(addr: Option<SocketAddr>, control_message: Option<ControlMessage>) -> Self {
+    pub const fn new(addr: Option<SocketAddr>, control_messages: Vec<ControlMessage>) -> Self {
         Self {
             addr,
-            control_message,
+            control_messages,
         }
     }
 
@@ -23,10 +29,71 @@ impl MessageHeader {
     pub fn addr(&self) -> Option<&SocketAddr> {
         self.addr.as_ref()
     }
+
+    /// Returns the control messages.
+    pub fn control_messages(&self) -> &[ControlMessage] {
+        &self.control_messages
+    }
 }
 
 /// Control message carried by MessageHeader.
 ///
-/// TODO: Implement the struct. The struct is empty now.
-#[derive(Debug)]
-pub struct ControlMessage;
+/// Currently, only the control messages of the `SOL_SOCKET` level can be sent, while the TTL and
+/// the hop limit of the received packets can also be received.
+pub enum ControlMessage {
+    /// Files passed with `SCM_RIGHTS`.
+    Files(Vec<Arc<dyn FileLike>>),
+    /// Credentials passed with `SCM_CREDENTIALS`.
+    Credentials(UCred),
+    /// The TTL of a received IPv4 packet, reported with `IP_TTL`.
+    Ttl(u8),
+    /// The hop limit of a received IPv6 packet, reported with `IPV6_HOPLIMIT`.
+    HopLimit(u8),
+}
+
+impl fmt::Debug for ControlMessage {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Files(files) => f.debug_tuple("Files").field(&files.len()).finish(),
+            Self::Credentials(cred) => f.debug_tuple("Credentials").field(cred).finish(),
+            Self::Ttl(ttl) => f.debug_tuple("Ttl").field(ttl).finish(),
+            Self::HopLimit(hop_limit) => f.debug_tuple("HopLimit").field(hop_limit).finish(),
+        }
+    }
+}
+
+/// The credentials of a process, as in `struct ucred`.
+///
+/// The credentials are used by `SO_PEERCRED` and `SCM_CREDENTIALS`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct UCred {
+    pub pid: Pid,
+    pub uid: Uid,
+    pub gid: Gid,
+}
+
+impl UCred {
+    /// Returns the credentials of the current process.
+    ///
+    /// The effective user and group IDs are used.
+    pub fn new_current() -> Self {
+        let current = current_thread!();
+        let posix_thread = current.as_posix_thread().unwrap();
+        let credentials = posix_thread.credentials();
+
+        Self {
+            pid: posix_thread.process().pid(),
+            uid: credentials.euid(),
+            gid: credentials.egid(),
+        }
+    }
+
+    /// Returns invalid credentials, which are used if the credentials are not available.
+    pub const fn new_invalid() -> Self {
+        Self {
+            pid: 0,
+            uid: Uid::new(u32::MAX),
+            gid: Gid::new(u32::MAX),
+        }
+    }
+}
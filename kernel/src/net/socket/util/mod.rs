@@ -1,9 +1,10 @@
 // SPDX-License-Identifier: MPL-2.0
 
+pub mod filter;
 mod message_header;
 pub mod options;
 pub mod send_recv_flags;
 pub mod shutdown_cmd;
 pub mod socket_addr;
 
-pub use message_header::MessageHeader;
+pub use message_header::{ControlMessage, MessageHeader, UCred};
@@ -15,6 +15,11 @@ use crate::{
     prelude::*,
 };
 
+/// The default buffer length of netlink and packet sockets.
+///
+/// This is the same as the default value of `net.core.rmem_default` in Linux.
+const CORE_DEFAULT_BUF_LEN: u32 = 212992;
+
 #[derive(Debug, Clone, CopyGetters, Setters)]
 #[get_copy = "pub"]
 #[set = "pub"]
@@ -55,6 +60,45 @@ impl SocketOptionSet {
         }
     }
 
+    /// Return the default socket level options for netlink socket.
+    pub fn new_netlink() -> Self {
+        Self {
+            sock_errors: None,
+            reuse_addr: false,
+            reuse_port: false,
+            send_buf: CORE_DEFAULT_BUF_LEN,
+            recv_buf: CORE_DEFAULT_BUF_LEN,
+            linger: LingerOption::default(),
+            keep_alive: false,
+        }
+    }
+
+    /// Return the default socket level options for packet socket.
+    pub fn new_packet() -> Self {
+        Self {
+            sock_errors: None,
+            reuse_addr: false,
+            reuse_port: false,
+            send_buf: CORE_DEFAULT_BUF_LEN,
+            recv_buf: CORE_DEFAULT_BUF_LEN,
+            linger: LingerOption::default(),
+            keep_alive: false,
+        }
+    }
+
+    /// Return the default socket level options for raw socket.
+    pub fn new_raw() -> Self {
+        Self {
+            sock_errors: None,
+            reuse_addr: false,
+            reuse_port: false,
+            send_buf: CORE_DEFAULT_BUF_LEN,
+            recv_buf: CORE_DEFAULT_BUF_LEN,
+            linger: LingerOption::default(),
+            keep_alive: false,
+        }
+    }
+
     /// Gets and clears the socket error.
     ///
     /// When processing the `getsockopt` system call, the socket error is automatically cleared
@@ -0,0 +1,388 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! Control groups (cgroups) with the unified hierarchy semantics of cgroup v2.
+//!
+//! Every process belongs to exactly one cgroup. A new process belongs to the cgroup of its
+//! parent, and the first process belongs to [`Cgroup::root`].
+//!
+//! A controller in a cgroup is enabled if the controller is enabled in the `subtree_control` of
+//! the parent. The root cgroup has all the controllers enabled. If a controller is not enabled
+//! in a cgroup, the processes in the cgroup are controlled by the nearest ancestor in which the
+//! controller is enabled.
+
+use ostd::cpu::CpuSet;
+use spin::Once;
+
+use super::{process_table, Process};
+use crate::{
+    prelude::*,
+    sched::{
+        cpuset::{self, Cpuset},
+        SchedGroup,
+    },
+    thread::AsThread,
+    vm::memcg::MemoryGroup,
+};
+
+bitflags! {
+    /// The controllers of cgroups.
+    pub struct Controllers: u8 {
+        const CPUSET = 1 << 0;
+        const CPU    = 1 << 1;
+        const MEMORY = 1 << 2;
+    }
+}
+
+impl Controllers {
+    const NAMES: [(Controllers, &'static str); 3] = [
+        (Controllers::CPUSET, "cpuset"),
+        (Controllers::CPU, "cpu"),
+        (Controllers::MEMORY, "memory"),
+    ];
+
+    /// Parses the name of a controller.
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::NAMES
+            .iter()
+            .find(|(_, controller_name)| *controller_name == name)
+            .map(|(controller, _)| *controller)
+    }
+
+    /// Returns the space-separated names of the controllers.
+    pub fn to_names(self) -> String {
+        let names: Vec<_> = Self::NAMES
+            .iter()
+            .filter(|(controller, _)| self.contains(*controller))
+            .map(|(_, name)| *name)
+            .collect();
+        names.join(" ")
+    }
+}
+
+/// A control group.
+pub struct Cgroup {
+    name: String,
+    parent: Option<Arc<Cgroup>>,
+    inner: Mutex<CgroupInner>,
+    sched_group: Arc<SchedGroup>,
+    memory_group: Arc<MemoryGroup>,
+    cpuset: Arc<Cpuset>,
+}
+
+struct CgroupInner {
+    children: BTreeMap<String, Arc<Cgroup>>,
+    subtree_control: Controllers,
+}
+
+/// The lock that serializes the changes to the hierarchy and the migration of processes.
+static HIERARCHY_LOCK: Mutex<()> = Mutex::new(());
+
+impl Cgroup {
+    /// Returns the root cgroup.
+    pub fn root() -> &'static Arc<Cgroup> {
+        static ROOT: Once<Arc<Cgroup>> = Once::new();
+
+        ROOT.call_once(|| {
+            Arc::new(Self {
+                name: String::new(),
+                parent: None,
+                inner: Mutex::new(CgroupInner {
+                    children: BTreeMap::new(),
+                    subtree_control: Controllers::empty(),
+                }),
+                sched_group: SchedGroup::root().clone(),
+                memory_group: MemoryGroup::root().clone(),
+                cpuset: Cpuset::root().clone(),
+            })
+        })
+    }
+
+    /// Returns the parent cgroup, or `None` for the root cgroup.
+    pub fn parent(&self) -> Option<&Arc<Cgroup>> {
+        self.parent.as_ref()
+    }
+
+    /// Returns the path of the cgroup relative to the root cgroup.
+    pub fn path(&self) -> String {
+        let Some(parent) = &self.parent else {
+            return String::from("/");
+        };
+
+        let mut path = parent.path();
+        if !path.ends_with('/') {
+            path.push('/');
+        }
+        path.push_str(&self.name);
+        path
+    }
+
+    /// Returns the child cgroup with the name.
+    pub fn child(&self, name: &str) -> Option<Arc<Cgroup>> {
+        self.inner.lock().children.get(name).cloned()
+    }
+
+    /// Creates a child cgroup.
+    pub fn new_child(self: &Arc<Self>, name: &str) -> Result<Arc<Cgroup>> {
+        let _guard = HIERARCHY_LOCK.lock();
+
+        if self.is_removed() {
+            return_errno_with_message!(Errno::ENOENT, "the cgroup has been removed");
+        }
+        let cpus = self.effective_cpuset().cpus();
+
+        let mut inner = self.inner.lock();
+        if inner.children.contains_key(name) {
+            return_errno_with_message!(Errno::EEXIST, "the cgroup already exists");
+        }
+
+        let child = Arc::new(Self {
+            name: name.to_string(),
+            parent: Some(self.clone()),
+            inner: Mutex::new(CgroupInner {
+                children: BTreeMap::new(),
+                subtree_control: Controllers::empty(),
+            }),
+            sched_group: self.sched_group.new_child(),
+            memory_group: self.memory_group.new_child(),
+            cpuset: Cpuset::new(cpus),
+        });
+        inner.children.insert(name.to_string(), child.clone());
+
+        Ok(child)
+    }
+
+    /// Removes a child cgroup, which must contain no child cgroups nor processes.
+    pub fn remove_child(&self, name: &str) -> Result<()> {
+        let _guard = HIERARCHY_LOCK.lock();
+
+        let mut inner = self.inner.lock();
+        let Some(child) = inner.children.get(name) else {
+            return_errno_with_message!(Errno::ENOENT, "the cgroup does not exist");
+        };
+        if !child.inner.lock().children.is_empty() {
+            return_errno_with_message!(Errno::EBUSY, "the cgroup has child cgroups");
+        }
+        if child.is_populated() {
+            return_errno_with_message!(Errno::EBUSY, "the cgroup has processes");
+        }
+
+        inner.children.remove(name);
+        Ok(())
+    }
+
+    /// Returns the controllers that are enabled in the cgroup.
+    pub fn controllers(&self) -> Controllers {
+        match &self.parent {
+            Some(parent) => parent.subtree_control(),
+            None => Controllers::all(),
+        }
+    }
+
+    /// Returns the controllers that are enabled in the child cgroups.
+    pub fn subtree_control(&self) -> Controllers {
+        self.inner.lock().subtree_control
+    }
+
+    /// Enables and disables the controllers in the child cgroups.
+    pub fn update_subtree_control(&self, enable: Controllers, disable: Controllers) -> Result<()> {
+        let _guard = HIERARCHY_LOCK.lock();
+
+        if !self.controllers().contains(enable) {
+            return_errno_with_message!(Errno::ENOENT, "the controllers are not enabled");
+        }
+        // The "no internal processes" rule: Only the root cgroup can have both processes and
+        // controllers enabled in the child cgroups.
+        if !enable.is_empty() && self.parent.is_some() && self.is_populated() {
+            return_errno_with_message!(Errno::EBUSY, "the cgroup has processes");
+        }
+
+        let children = {
+            let mut inner = self.inner.lock();
+            let children: Vec<_> = inner.children.values().cloned().collect();
+            if children
+                .iter()
+                .any(|child| child.subtree_control().intersects(disable))
+            {
+                return_errno_with_message!(
+                    Errno::EBUSY,
+                    "the controllers are enabled in the subtree"
+                );
+            }
+
+            inner.subtree_control = (inner.subtree_control | enable) - disable;
+            children
+        };
+
+        // The processes in the subtree may be controlled by different cgroups now.
+        children.iter().for_each(|child| child.refresh_processes());
+        Ok(())
+    }
+
+    /// Returns the CPU controller of the cgroup.
+    pub fn sched_group(&self) -> &Arc<SchedGroup> {
+        &self.sched_group
+    }
+
+    /// Returns the memory controller of the cgroup.
+    pub fn memory_group(&self) -> &Arc<MemoryGroup> {
+        &self.memory_group
+    }
+
+    /// Returns the cpuset controller of the cgroup.
+    pub fn cpuset(&self) -> &Arc<Cpuset> {
+        &self.cpuset
+    }
+
+    /// Returns the CPU controller that controls the processes in the cgroup.
+    pub fn effective_sched_group(&self) -> &Arc<SchedGroup> {
+        &self.nearest_enabled(Controllers::CPU).sched_group
+    }
+
+    /// Returns the memory controller that controls the processes in the cgroup.
+    pub fn effective_memory_group(&self) -> &Arc<MemoryGroup> {
+        &self.nearest_enabled(Controllers::MEMORY).memory_group
+    }
+
+    /// Returns the cpuset controller that controls the processes in the cgroup.
+    pub fn effective_cpuset(&self) -> &Arc<Cpuset> {
+        &self.nearest_enabled(Controllers::CPUSET).cpuset
+    }
+
+    fn nearest_enabled(&self, controller: Controllers) -> &Cgroup {
+        let mut cgroup = self;
+        while let Some(parent) = &cgroup.parent {
+            if parent.subtree_control().contains(controller) {
+                break;
+            }
+            cgroup = parent;
+        }
+        cgroup
+    }
+
+    /// Sets the CPUs of the cpuset controller of the cgroup.
+    ///
+    /// An empty set means all the CPUs of the parent.
+    pub fn set_cpus(&self, cpus: CpuSet) -> Result<()> {
+        let _guard = HIERARCHY_LOCK.lock();
+
+        let Some(parent) = &self.parent else {
+            return_errno_with_message!(Errno::EINVAL, "the CPUs of the root cgroup are fixed");
+        };
+        let parent_cpus = parent.effective_cpuset().cpus();
+        let cpus = if cpus.is_empty() { parent_cpus } else { cpus };
+
+        if cpus.iter().any(|cpu| !parent_cpus.contains(cpu)) {
+            return_errno_with_message!(Errno::EINVAL, "the CPUs are not in the parent cgroup");
+        }
+        let inner = self.inner.lock();
+        if inner
+            .children
+            .values()
+            .any(|child| child.cpuset.cpus().iter().any(|cpu| !cpus.contains(cpu)))
+        {
+            return_errno_with_message!(Errno::EBUSY, "the CPUs are used by the child cgroups");
+        }
+        drop(inner);
+
+        self.cpuset.set_cpus(cpus);
+        self.refresh_processes();
+        Ok(())
+    }
+
+    /// Returns the processes in the cgroup, excluding the ones in the child cgroups.
+    pub fn processes(&self) -> Vec<Arc<Process>> {
+        process_table::process_table_mut()
+            .iter()
+            .filter(|process| {
+                !process.status().is_zombie() && core::ptr::eq(&*process.cgroup(), self)
+            })
+            .cloned()
+            .collect()
+    }
+
+    fn is_populated(&self) -> bool {
+        !self.processes().is_empty()
+    }
+
+    /// Moves a process into the cgroup.
+    pub fn attach(self: &Arc<Self>, process: &Process) -> Result<()> {
+        let _guard = HIERARCHY_LOCK.lock();
+
+        if self.is_removed() {
+            return_errno_with_message!(Errno::ENODEV, "the cgroup has been removed");
+        }
+        if self.parent.is_some() && !self.subtree_control().is_empty() {
+            return_errno_with_message!(
+                Errno::EBUSY,
+                "the controllers are enabled in the child cgroups"
+            );
+        }
+
+        process.set_cgroup(self.clone());
+        self.apply_to(process);
+        Ok(())
+    }
+
+    /// Applies the controllers to the processes in the subtree of the cgroup.
+    fn refresh_processes(&self) {
+        let processes: Vec<_> = process_table::process_table_mut().iter().cloned().collect();
+        for process in processes {
+            let cgroup = process.cgroup();
+            if cgroup.is_descendant_of(self) {
+                cgroup.apply_to(&process);
+            }
+        }
+    }
+
+    fn is_removed(&self) -> bool {
+        let Some(parent) = &self.parent else {
+            return false;
+        };
+        parent
+            .child(&self.name)
+            .is_none_or(|child| !core::ptr::eq(&*child, self))
+    }
+
+    fn is_descendant_of(&self, ancestor: &Cgroup) -> bool {
+        let mut cgroup = self;
+        loop {
+            if core::ptr::eq(cgroup, ancestor) {
+                return true;
+            }
+            let Some(parent) = &cgroup.parent else {
+                return false;
+            };
+            cgroup = parent;
+        }
+    }
+
+    /// Applies the CPU and cpuset controllers to the threads of the process.
+    ///
+    /// The memory controller charges the memory when it is allocated, so it needs no updates.
+    fn apply_to(&self, process: &Process) {
+        let sched_group = self.effective_sched_group();
+        let cpus = self.effective_cpuset().cpus();
+
+        for task in process.tasks().lock().as_slice() {
+            let thread = task.as_thread().unwrap();
+            thread.sched_attr().set_group(sched_group.clone());
+
+            let affinity = thread.atomic_cpu_affinity();
+            let mut new_affinity = cpuset::intersect(&affinity.load(), &cpus);
+            if new_affinity.is_empty() {
+                new_affinity = cpus.clone();
+            }
+            // The threads that are running on the CPUs outside the new affinity will be
+            // migrated when they are switched out.
+            affinity.store(&new_affinity);
+        }
+    }
+}
+
+impl Debug for Cgroup {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("Cgroup")
+            .field("path", &self.path())
+            .finish_non_exhaustive()
+    }
+}
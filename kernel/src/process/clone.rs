@@ -4,22 +4,27 @@ use core::{num::NonZeroU64, sync::atomic::Ordering};
 
 use ostd::{
     cpu::UserContext,
-    sync::RwArc,
+    sync::{RwArc, Waiter, Waker},
     task::Task,
     user::{UserContextApi, UserSpace},
 };
 
 use super::{
-    posix_thread::{AsPosixThread, PosixThreadBuilder, ThreadName},
+    credentials::capabilities::CapSet,
+    namespace::NsProxy,
+    posix_thread::{AsPosixThread, PosixThread, PosixThreadBuilder, ThreadName},
     process_table,
     process_vm::ProcessVm,
-    signal::{constants::SIGCHLD, sig_disposition::SigDispositions, sig_num::SigNum},
-    Credentials, Process, ProcessBuilder,
+    signal::{constants::SIGCHLD, sig_disposition::SigDispositions, sig_num::SigNum, SigStack},
+    Credentials, PidFile, Process, ProcessBuilder,
 };
 use crate::{
     cpu::LinuxAbi,
     current_userspace,
-    fs::{file_table::FileTable, thread_info::ThreadFsInfo},
+    fs::{
+        file_table::{max_fds, FdFlags, FileTable},
+        thread_info::ThreadFsInfo,
+    },
     prelude::*,
     process::posix_thread::allocate_posix_tid,
     thread::{AsThread, Tid},
@@ -82,7 +87,7 @@ bitflags! {
 #[derive(Debug, Clone, Copy, Default)]
 pub struct CloneArgs {
     pub flags: CloneFlags,
-    pub _pidfd: Option<u64>,
+    pub pidfd: Option<Vaddr>,
     pub child_tid: Vaddr,
     pub parent_tid: Option<Vaddr>,
     pub exit_signal: Option<SigNum>,
@@ -114,7 +119,7 @@ impl CloneArgs {
             flags.contains(CloneFlags::CLONE_PARENT_SETTID),
         ) {
             (false, false) => (None, None),
-            (true, false) => (Some(parent_tid as u64), None),
+            (true, false) => (Some(parent_tid), None),
             (false, true) => (None, Some(parent_tid)),
             (true, true) => {
                 return_errno_with_message!(
@@ -126,10 +131,12 @@ impl CloneArgs {
 
         Ok(Self {
             flags,
-            _pidfd: pidfd,
+            pidfd,
             child_tid,
             parent_tid,
-            exit_signal: (exit_signal != 0).then(|| SigNum::from_u8(exit_signal as u8)),
+            exit_signal: (exit_signal != 0)
+                .then(|| SigNum::try_from(exit_signal as u8))
+                .transpose()?,
             stack,
             tls,
             ..Default::default()
@@ -142,6 +149,18 @@ impl CloneArgs {
             ..Default::default()
         }
     }
+
+    /// Prepares a new [`CloneArgs`] for vfork(2).
+    ///
+    /// The child shares the address space with the parent, so no page tables are copied. The
+    /// parent is suspended until the child calls `execve` or exits.
+    pub fn for_vfork() -> Self {
+        Self {
+            flags: CloneFlags::CLONE_VM | CloneFlags::CLONE_VFORK,
+            exit_signal: Some(SIGCHLD),
+            ..Default::default()
+        }
+    }
 }
 
 impl From<u64> for CloneFlags {
@@ -158,18 +177,72 @@ impl CloneFlags {
             | CloneFlags::CLONE_FS
             | CloneFlags::CLONE_FILES
             | CloneFlags::CLONE_SIGHAND
+            | CloneFlags::CLONE_PIDFD
+            | CloneFlags::CLONE_VFORK
+            | CloneFlags::CLONE_PARENT
             | CloneFlags::CLONE_THREAD
             | CloneFlags::CLONE_SYSVSEM
             | CloneFlags::CLONE_SETTLS
             | CloneFlags::CLONE_PARENT_SETTID
             | CloneFlags::CLONE_CHILD_SETTID
-            | CloneFlags::CLONE_CHILD_CLEARTID;
+            | CloneFlags::CLONE_CHILD_CLEARTID
+            | NsProxy::supported_flags();
         let unsupported_flags = *self - supported_flags;
         if !unsupported_flags.is_empty() {
             warn!("contains unsupported clone flags: {:?}", unsupported_flags);
         }
         Ok(())
     }
+
+    /// Checks the combinations of the flags that are not valid.
+    ///
+    /// The rules follow the "ERRORS" section of the Linux man pages. See
+    /// <https://www.man7.org/linux/man-pages/man2/clone.2.html>.
+    fn check_invalid_flags(&self) -> Result<()> {
+        if self.contains(CloneFlags::CLONE_NEWNS | CloneFlags::CLONE_FS) {
+            return_errno_with_message!(
+                Errno::EINVAL,
+                "`CLONE_NEWNS` and `CLONE_FS` cannot be specified together"
+            );
+        }
+        if self.contains(CloneFlags::CLONE_NEWUSER | CloneFlags::CLONE_FS) {
+            return_errno_with_message!(
+                Errno::EINVAL,
+                "`CLONE_NEWUSER` and `CLONE_FS` cannot be specified together"
+            );
+        }
+        if self.contains(CloneFlags::CLONE_NEWIPC | CloneFlags::CLONE_SYSVSEM) {
+            return_errno_with_message!(
+                Errno::EINVAL,
+                "`CLONE_NEWIPC` and `CLONE_SYSVSEM` cannot be specified together"
+            );
+        }
+        if self.contains(CloneFlags::CLONE_NEWPID | CloneFlags::CLONE_THREAD) {
+            return_errno_with_message!(
+                Errno::EINVAL,
+                "`CLONE_NEWPID` and `CLONE_THREAD` cannot be specified together"
+            );
+        }
+        if self.contains(CloneFlags::CLONE_THREAD) && !self.contains(CloneFlags::CLONE_SIGHAND) {
+            return_errno_with_message!(
+                Errno::EINVAL,
+                "`CLONE_THREAD` is specified without `CLONE_SIGHAND`"
+            );
+        }
+        if self.contains(CloneFlags::CLONE_PIDFD | CloneFlags::CLONE_THREAD) {
+            return_errno_with_message!(
+                Errno::EINVAL,
+                "`CLONE_PIDFD` and `CLONE_THREAD` cannot be specified together"
+            );
+        }
+        if self.contains(CloneFlags::CLONE_SIGHAND) && !self.contains(CloneFlags::CLONE_VM) {
+            return_errno_with_message!(
+                Errno::EINVAL,
+                "`CLONE_SIGHAND` is specified without `CLONE_VM`"
+            );
+        }
+        Ok(())
+    }
 }
 
 /// Clone a child thread or child process.
@@ -182,38 +255,61 @@ pub fn clone_child(
     clone_args: CloneArgs,
 ) -> Result<Tid> {
     clone_args.flags.check_unsupported_flags()?;
-    if clone_args.flags.contains(CloneFlags::CLONE_THREAD) {
-        let child_task = clone_child_task(ctx, parent_context, clone_args)?;
+    clone_args.flags.check_invalid_flags()?;
+
+    if clone_args.flags.intersects(NsProxy::supported_flags())
+        && !ctx
+            .posix_thread
+            .credentials()
+            .effective_capset()
+            .contains(CapSet::SYS_ADMIN)
+    {
+        return_errno_with_message!(Errno::EPERM, "creating a namespace requires CAP_SYS_ADMIN");
+    }
+
+    // The parent is suspended until the child calls `execve` or exits.
+    let (vfork_waiter, vfork_done) = if clone_args.flags.contains(CloneFlags::CLONE_VFORK) {
+        let (waiter, waker) = Waiter::new_pair();
+        (Some(waiter), Some(waker))
+    } else {
+        (None, None)
+    };
+
+    // The child may be in a new PID namespace, but it is always visible to the parent. The ID
+    // must be translated before the child runs, since the child may exit and free its IDs.
+    let pid_ns = ctx.process.pid_ns();
+    let child_tid = if clone_args.flags.contains(CloneFlags::CLONE_THREAD) {
+        let child_task = clone_child_task(ctx, parent_context, clone_args, vfork_done)?;
         let child_thread = child_task.as_thread().unwrap();
+        let child_tid = child_thread.as_posix_thread().unwrap().tid();
+        let child_tid = pid_ns.local_id(child_tid).unwrap();
         child_thread.run();
 
-        let child_tid = child_thread.as_posix_thread().unwrap().tid();
-        Ok(child_tid)
+        child_tid
     } else {
-        let child_process = clone_child_process(ctx, parent_context, clone_args)?;
+        let child_process = clone_child_process(ctx, parent_context, clone_args, vfork_done)?;
+        let child_tid = pid_ns.local_id(child_process.pid()).unwrap();
         child_process.run();
 
-        let child_pid = child_process.pid();
-        Ok(child_pid)
+        child_tid
+    };
+
+    if let Some(waiter) = vfork_waiter {
+        // TODO: The wait should be interrupted if the parent is killed.
+        waiter.wait();
     }
+
+    Ok(child_tid)
 }
 
 fn clone_child_task(
     ctx: &Context,
     parent_context: &UserContext,
     clone_args: CloneArgs,
+    vfork_done: Option<Arc<Waker>>,
 ) -> Result<Arc<Task>> {
     let clone_flags = clone_args.flags;
 
-    // This combination is not valid, according to the Linux man pages. See
-    // <https://www.man7.org/linux/man-pages/man2/clone.2.html>.
-    if !clone_flags.contains(CloneFlags::CLONE_VM | CloneFlags::CLONE_SIGHAND) {
-        return_errno_with_message!(
-            Errno::EINVAL,
-            "`CLONE_THREAD` without `CLONE_VM` and `CLONE_SIGHAND` is not valid"
-        );
-    }
-
     let Context {
         process,
         thread_local,
@@ -228,11 +324,13 @@ fn clone_child_task(
     let child_file_table = clone_files(&thread_local.file_table().borrow(), clone_flags);
 
     // clone fs
-    let child_fs = clone_fs(posix_thread.fs(), clone_flags);
+    let child_fs = clone_fs(&thread_local.borrow_fs(), clone_flags);
+
+    // clone namespaces
+    let child_ns_proxy = clone_ns_proxy(posix_thread, &child_fs, clone_flags)?;
 
-    let child_root_vmar = process.root_vmar();
     let child_user_space = {
-        let child_vm_space = child_root_vmar.vm_space().clone();
+        let child_vm_space = thread_local.root_vmar().vm_space().clone();
         let child_cpu_context = clone_cpu_context(
             parent_context,
             clone_args.stack,
@@ -246,7 +344,21 @@ fn clone_child_task(
     // Inherit sigmask from current thread
     let sig_mask = posix_thread.sig_mask().load(Ordering::Relaxed).into();
 
+    // clone the alternate signal stack
+    let sig_stack = clone_sig_stack(&thread_local.sig_stack().borrow(), clone_flags);
+
+    // The threads in a process must be in the same PID namespace.
+    let pid_ns = process.pid_ns();
+    if !Arc::ptr_eq(pid_ns, child_ns_proxy.pid_ns_for_children()) {
+        return_errno_with_message!(
+            Errno::EINVAL,
+            "the PID namespace for children differs from the one of the process"
+        );
+    }
+
     let child_tid = allocate_posix_tid();
+    pid_ns.attach(child_tid)?;
+
     let child_task = {
         let credentials = {
             let credentials = ctx.posix_thread.credentials();
@@ -256,22 +368,38 @@ fn clone_child_task(
         let mut thread_builder = PosixThreadBuilder::new(child_tid, child_user_space, credentials)
             .process(posix_thread.weak_process())
             .sig_mask(sig_mask)
+            .sig_stack(sig_stack)
             .file_table(child_file_table)
-            .fs(child_fs);
+            .fs(child_fs)
+            .ns_proxy(child_ns_proxy)
+            .no_new_privs(posix_thread.no_new_privs())
+            .seccomp(posix_thread.seccomp().lock().clone())
+            .landlock_domain(posix_thread.landlock_domain().lock().clone())
+            .keyrings(posix_thread.keyrings().lock().new_thread())
+            .memory_policy(posix_thread.memory_policy().lock().clone())
+            .sched_policy(ctx.thread.sched_attr().policy())
+            .sched_group(ctx.thread.sched_attr().group())
+            .cpu_affinity(ctx.thread.atomic_cpu_affinity().load());
 
         // Deal with SETTID/CLEARTID flags
-        clone_parent_settid(child_tid, clone_args.parent_tid, clone_flags)?;
+        let ns_tid = pid_ns.local_id(child_tid).unwrap();
+        clone_parent_settid(ns_tid, clone_args.parent_tid, clone_flags)
+            .inspect_err(|_| pid_ns.detach(child_tid))?;
         thread_builder = clone_child_cleartid(thread_builder, clone_args.child_tid, clone_flags);
         thread_builder = clone_child_settid(thread_builder, clone_args.child_tid, clone_flags);
 
-        thread_builder.build()
+        thread_builder.vfork_done(vfork_done).build()
     };
 
+    // The IDs are freed when the thread exits. If the thread cannot run, we have to free them now.
     process
         .tasks()
         .lock()
         .insert(child_task.clone())
-        .map_err(|_| Error::with_message(Errno::EINTR, "the process has exited"))?;
+        .map_err(|_| {
+            pid_ns.detach(child_tid);
+            Error::with_message(Errno::EINTR, "the process has exited")
+        })?;
 
     Ok(child_task)
 }
@@ -280,6 +408,7 @@ fn clone_child_process(
     ctx: &Context,
     parent_context: &UserContext,
     clone_args: CloneArgs,
+    vfork_done: Option<Arc<Waker>>,
 ) -> Result<Arc<Process>> {
     let Context {
         process,
@@ -290,12 +419,22 @@ fn clone_child_process(
 
     let clone_flags = clone_args.flags;
 
-    // clone vm
-    let child_process_vm = {
-        let parent_process_vm = process.vm();
-        clone_vm(parent_process_vm, clone_flags)?
+    // With `CLONE_PARENT`, the child becomes a sibling of the current process.
+    let (child_parent, child_exit_signal) = if clone_flags.contains(CloneFlags::CLONE_PARENT) {
+        let Some(parent) = process.parent().lock().process().upgrade() else {
+            return_errno_with_message!(
+                Errno::EINVAL,
+                "`CLONE_PARENT` cannot be specified by the init process"
+            );
+        };
+        (parent, process.exit_signal())
+    } else {
+        (posix_thread.process(), clone_args.exit_signal)
     };
 
+    // clone vm
+    let child_process_vm = clone_vm(&thread_local.process_vm(), clone_flags)?;
+
     // clone user space
     let child_user_space = {
         let child_cpu_context = clone_cpu_context(
@@ -316,7 +455,10 @@ fn clone_child_process(
     let child_file_table = clone_files(&thread_local.file_table().borrow(), clone_flags);
 
     // clone fs
-    let child_fs = clone_fs(posix_thread.fs(), clone_flags);
+    let child_fs = clone_fs(&thread_local.borrow_fs(), clone_flags);
+
+    // clone namespaces
+    let child_ns_proxy = clone_ns_proxy(posix_thread, &child_fs, clone_flags)?;
 
     // clone sig dispositions
     let child_sig_dispositions = clone_sighand(process.sig_dispositions(), clone_flags);
@@ -327,16 +469,22 @@ fn clone_child_process(
     // inherit parent's sig mask
     let child_sig_mask = posix_thread.sig_mask().load(Ordering::Relaxed).into();
 
+    // clone the alternate signal stack
+    let child_sig_stack = clone_sig_stack(&thread_local.sig_stack().borrow(), clone_flags);
+
     // inherit parent's nice value
     let child_nice = process.nice().load(Ordering::Relaxed);
 
+    let child_elf_path = process.executable_path();
+    let child_thread_name = ThreadName::new_from_executable_path(&child_elf_path)?;
+
+    // With `CLONE_NEWPID`, the child is the first process in the new PID namespace.
+    let child_pid_ns = child_ns_proxy.pid_ns_for_children().clone();
     let child_tid = allocate_posix_tid();
+    child_pid_ns.attach(child_tid)?;
 
     let child = {
-        let child_elf_path = process.executable_path();
         let mut child_thread_builder = {
-            let child_thread_name = ThreadName::new_from_executable_path(&child_elf_path)?;
-
             let credentials = {
                 let credentials = ctx.posix_thread.credentials();
                 Credentials::new_from(&credentials)
@@ -345,35 +493,60 @@ fn clone_child_process(
             PosixThreadBuilder::new(child_tid, child_user_space, credentials)
                 .thread_name(Some(child_thread_name))
                 .sig_mask(child_sig_mask)
+                .sig_stack(child_sig_stack)
                 .file_table(child_file_table)
                 .fs(child_fs)
+                .ns_proxy(child_ns_proxy)
+                .no_new_privs(posix_thread.no_new_privs())
+                .seccomp(posix_thread.seccomp().lock().clone())
+                .landlock_domain(posix_thread.landlock_domain().lock().clone())
+                .keyrings(posix_thread.keyrings().lock().new_process())
+                .memory_policy(posix_thread.memory_policy().lock().clone())
+                .sched_policy(ctx.thread.sched_attr().policy())
+                .sched_group(ctx.thread.sched_attr().group())
+                .cpu_affinity(ctx.thread.atomic_cpu_affinity().load())
         };
 
         // Deal with SETTID/CLEARTID flags
-        clone_parent_settid(child_tid, clone_args.parent_tid, clone_flags)?;
+        let parent_view_tid = process.pid_ns().local_id(child_tid).unwrap();
+        clone_parent_settid(parent_view_tid, clone_args.parent_tid, clone_flags)
+            .inspect_err(|_| child_pid_ns.detach(child_tid))?;
         child_thread_builder =
             clone_child_cleartid(child_thread_builder, clone_args.child_tid, clone_flags);
         child_thread_builder =
             clone_child_settid(child_thread_builder, clone_args.child_tid, clone_flags);
+        child_thread_builder = child_thread_builder.vfork_done(vfork_done);
 
         let mut process_builder =
-            ProcessBuilder::new(child_tid, &child_elf_path, posix_thread.weak_process());
+            ProcessBuilder::new(child_tid, &child_elf_path, Arc::downgrade(&child_parent));
 
         process_builder
             .main_thread_builder(child_thread_builder)
             .process_vm(child_process_vm)
             .sig_dispositions(child_sig_dispositions)
-            .nice(child_nice);
+            .nice(child_nice)
+            .resource_limits(process.resource_limits().lock().clone())
+            .cgroup(process.cgroup())
+            .pid_ns(child_pid_ns.clone());
 
-        process_builder.build()?
+        // Once the process is built, the IDs are freed when it is dropped.
+        process_builder
+            .build()
+            .inspect_err(|_| child_pid_ns.detach(child_tid))?
     };
 
-    if let Some(sig) = clone_args.exit_signal {
+    if let Some(sig) = child_exit_signal {
         child.set_exit_signal(sig);
     };
+    child.set_dumpable(process.is_dumpable());
+    child.set_personality(process.personality());
+    child.set_oom_score_adj(process.oom_score_adj());
+
+    // Deal with the CLONE_PIDFD flag
+    clone_pidfd(ctx, &child, clone_args.pidfd, clone_flags)?;
 
     // Sets parent process and group for child process.
-    set_parent_and_group(process, &child);
+    set_parent_and_group(&child_parent, process, &child);
 
     Ok(child)
 }
@@ -415,13 +588,36 @@ fn clone_parent_settid(
     Ok(())
 }
 
+/// Installs a pidfd referring to the child process in the file table of the parent.
+fn clone_pidfd(
+    ctx: &Context,
+    child: &Arc<Process>,
+    pidfdptr: Option<Vaddr>,
+    clone_flags: CloneFlags,
+) -> Result<()> {
+    let Some(addr) = pidfdptr.filter(|_| clone_flags.contains(CloneFlags::CLONE_PIDFD)) else {
+        return Ok(());
+    };
+
+    let pid_file = Arc::new(PidFile::new(child.clone(), false));
+    let max_fds = max_fds(ctx.process);
+    let file_table = ctx.thread_local.file_table().borrow();
+    let mut file_table_locked = file_table.write();
+    let fd = file_table_locked.insert(pid_file, FdFlags::CLOEXEC, max_fds)?;
+    if let Err(err) = ctx.user_space().write_val(addr, &fd) {
+        file_table_locked.close_file(fd).unwrap();
+        return Err(err);
+    }
+    Ok(())
+}
+
 /// Clone child process vm. If CLONE_VM is set, both threads share the same root vmar.
 /// Otherwise, fork a new copy-on-write vmar.
-fn clone_vm(parent_process_vm: &ProcessVm, clone_flags: CloneFlags) -> Result<ProcessVm> {
+fn clone_vm(parent_process_vm: &Arc<ProcessVm>, clone_flags: CloneFlags) -> Result<Arc<ProcessVm>> {
     if clone_flags.contains(CloneFlags::CLONE_VM) {
         Ok(parent_process_vm.clone())
     } else {
-        ProcessVm::fork_from(parent_process_vm)
+        Ok(Arc::new(ProcessVm::fork_from(parent_process_vm)?))
     }
 }
 
@@ -469,6 +665,21 @@ fn clone_fs(parent_fs: &Arc<ThreadFsInfo>, clone_flags: CloneFlags) -> Arc<Threa
     }
 }
 
+/// Clones the namespaces of the parent thread.
+///
+/// If any of the `CLONE_NEW*` flags is set, the child will be placed in new namespaces.
+/// Since `CLONE_NEWNS` cannot be used along with `CLONE_FS`, the `child_fs` is always
+/// exclusively owned by the child when a new mount namespace is created.
+fn clone_ns_proxy(
+    parent: &PosixThread,
+    child_fs: &Arc<ThreadFsInfo>,
+    clone_flags: CloneFlags,
+) -> Result<Arc<NsProxy>> {
+    let parent_ns_proxy = parent.ns_proxy().lock().clone();
+    let mut child_resolver = child_fs.resolver().write();
+    parent_ns_proxy.copy_with_flags(clone_flags, &mut child_resolver)
+}
+
 fn clone_files(parent_file_table: &RwArc<FileTable>, clone_flags: CloneFlags) -> RwArc<FileTable> {
     // if CLONE_FILES is set, the child and parent shares the same file table
     // Otherwise, the child will deep copy a new file table.
@@ -492,6 +703,17 @@ fn clone_sighand(
     }
 }
 
+fn clone_sig_stack(parent_sig_stack: &SigStack, clone_flags: CloneFlags) -> SigStack {
+    // Like Linux, the alternate signal stack is disabled if the child shares the address space
+    // and runs concurrently with the parent, since they cannot use the same stack.
+    if clone_flags.contains(CloneFlags::CLONE_VM) && !clone_flags.contains(CloneFlags::CLONE_VFORK)
+    {
+        SigStack::default()
+    } else {
+        parent_sig_stack.clone()
+    }
+}
+
 fn clone_sysvsem(clone_flags: CloneFlags) -> Result<()> {
     if clone_flags.contains(CloneFlags::CLONE_SYSVSEM) {
         warn!("CLONE_SYSVSEM is not supported now");
@@ -499,8 +721,14 @@ fn clone_sysvsem(clone_flags: CloneFlags) -> Result<()> {
     Ok(())
 }
 
-fn set_parent_and_group(parent: &Process, child: &Arc<Process>) {
-    let process_group = parent.process_group().unwrap();
+/// Adds the child to the children of `parent` and to the process group of `current`.
+///
+/// The two processes differ only if `CLONE_PARENT` is specified.
+//
+// FIXME: If `CLONE_PARENT` is specified and the parent exits concurrently, the child may be added
+// to the children of a zombie process and will never be moved to the init process.
+fn set_parent_and_group(parent: &Process, current: &Process, child: &Arc<Process>) {
+    let process_group = current.process_group().unwrap();
 
     let mut process_table_mut = process_table::process_table_mut();
     let mut group_inner = process_group.inner.lock();
@@ -0,0 +1,509 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! Core dumps.
+//!
+//! When a process is killed by a signal whose default action is to dump core (e.g., `SIGSEGV`
+//! and `SIGABRT`), its memory mappings and register state are written to an ELF core file, which
+//! can be loaded by debuggers to debug the process offline.
+//!
+//! The name of the core file is specified by `/proc/sys/kernel/core_pattern`. For details, see
+//! <https://man7.org/linux/man-pages/man5/core.5.html>.
+
+use alloc::borrow::Cow;
+use core::{mem::size_of, ops::Range, sync::atomic::Ordering};
+
+use align_ext::AlignExt;
+use ostd::cpu::UserContext;
+
+use super::{signal::c_types::siginfo_t, ResourceType};
+use crate::{
+    arch::ptrace::PtraceRegs,
+    fs::{
+        file_handle::FileLike,
+        fs_resolver::{FsPath, AT_FDCWD},
+        utils::{AccessMode, CreationFlags, InodeType},
+    },
+    prelude::*,
+    time::{clocks::RealTimeClock, timeval_t},
+    vm::perms::VmPerms,
+};
+
+/// The maximum length of the core pattern.
+const MAX_CORE_PATTERN_LEN: usize = 127;
+
+static CORE_PATTERN: Mutex<Cow<'static, str>> = Mutex::new(Cow::Borrowed("core"));
+
+/// Returns the pattern of the core file names.
+pub fn core_pattern() -> String {
+    CORE_PATTERN.lock().to_string()
+}
+
+/// Sets the pattern of the core file names.
+///
+/// Like Linux, the pattern ends at the first newline, and is truncated if it is too long.
+pub fn set_core_pattern(pattern: &[u8]) -> Result<()> {
+    let pattern = pattern.split(|&b| b == b'\n' || b == 0).next().unwrap();
+    let len = pattern.len().min(MAX_CORE_PATTERN_LEN);
+    let pattern = core::str::from_utf8(&pattern[..len])
+        .map_err(|_| Error::with_message(Errno::EINVAL, "the pattern is not valid UTF-8"))?;
+
+    *CORE_PATTERN.lock() = Cow::Owned(pattern.to_string());
+    Ok(())
+}
+
+/// Dumps the current process into a core file because of the signal.
+///
+/// Returns whether the core file is written.
+pub(super) fn do_coredump(ctx: &Context, user_ctx: &UserContext, siginfo: &siginfo_t) -> bool {
+    match dump_into_core_file(ctx, user_ctx, siginfo) {
+        Ok(()) => true,
+        Err(err) => {
+            debug!("failed to dump the process into a core file: {:?}", err);
+            false
+        }
+    }
+}
+
+fn dump_into_core_file(ctx: &Context, user_ctx: &UserContext, siginfo: &siginfo_t) -> Result<()> {
+    let process = ctx.process;
+    if !process.is_dumpable() {
+        return_errno_with_message!(Errno::EPERM, "the process is not dumpable");
+    }
+
+    let limit = process
+        .resource_limits()
+        .lock()
+        .get_rlimit(ResourceType::RLIMIT_CORE)
+        .get_cur();
+    // Like Linux, a core file is not written if it cannot contain a page.
+    if limit < PAGE_SIZE as u64 {
+        return_errno_with_message!(Errno::EFBIG, "the core file size limit is too small");
+    }
+
+    let pattern = core_pattern();
+    if pattern.starts_with('|') {
+        warn!("piping core files to programs is not supported");
+        return_errno_with_message!(Errno::EOPNOTSUPP, "piping core files is not supported");
+    }
+    let path = expand_core_pattern(&pattern, ctx, siginfo, limit);
+
+    let mut writer = CoreWriter {
+        file: create_core_file(&path, ctx)?,
+        written: 0,
+        limit,
+    };
+    write_core(&mut writer, ctx, user_ctx, siginfo)
+}
+
+/// Expands the specifiers in the core pattern.
+fn expand_core_pattern(pattern: &str, ctx: &Context, siginfo: &siginfo_t, limit: u64) -> String {
+    let process = ctx.process;
+    let posix_thread = ctx.posix_thread;
+    let credentials = posix_thread.credentials();
+
+    let mut path = String::new();
+    let mut chars = pattern.chars();
+    while let Some(c) = chars.next() {
+        if c != '%' {
+            path.push(c);
+            continue;
+        }
+
+        let value = match chars.next() {
+            Some('%') => "%".to_string(),
+            Some('p') => process.ns_pid().to_string(),
+            Some('P') => process.pid().to_string(),
+            Some('i') => posix_thread.ns_tid().to_string(),
+            Some('I') => posix_thread.tid().to_string(),
+            Some('u') => u32::from(credentials.ruid()).to_string(),
+            Some('g') => u32::from(credentials.rgid()).to_string(),
+            Some('s') => siginfo.si_signo.to_string(),
+            Some('t') => RealTimeClock::get().read_time().as_secs().to_string(),
+            Some('c') => limit.to_string(),
+            // The values below may contain slashes, which are replaced by `!` as in Linux.
+            Some('h') => {
+                let uts_name = posix_thread.ns_proxy().lock().uts_ns().uts_name();
+                String::from_utf8_lossy(uts_name.nodename()).replace('/', "!")
+            }
+            Some('e') => thread_name(ctx).replace('/', "!"),
+            Some('E') => process.executable_path().replace('/', "!"),
+            // Like Linux, unknown specifiers are dropped.
+            _ => continue,
+        };
+        path.push_str(&value);
+    }
+
+    path
+}
+
+fn thread_name(ctx: &Context) -> String {
+    let thread_name = ctx.posix_thread.thread_name().lock();
+    thread_name
+        .as_ref()
+        .and_then(|name| name.name().ok().flatten())
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_default()
+}
+
+fn create_core_file(path: &str, ctx: &Context) -> Result<Arc<dyn FileLike>> {
+    let fs_path = FsPath::new(AT_FDCWD, path)?;
+    // Like Linux, the file is opened for reading and writing, so opening a FIFO does not block.
+    let flags =
+        AccessMode::O_RDWR as u32 | (CreationFlags::O_CREAT | CreationFlags::O_NOFOLLOW).bits();
+    let fs = ctx.thread_local.borrow_fs();
+    let mode = 0o600 & !fs.umask().read().get();
+    let file = fs.resolver().read().open(&fs_path, flags, mode)?;
+
+    // Refuse to write to the files that are not created for core dumps. Otherwise, a malicious
+    // user may trick the process into overwriting other files.
+    let dentry = file.dentry();
+    if dentry.type_() != InodeType::File {
+        return_errno_with_message!(Errno::EINVAL, "the core file is not a regular file");
+    }
+    if dentry.metadata().nlinks > 1 {
+        return_errno_with_message!(Errno::EPERM, "the core file has multiple links");
+    }
+    if dentry.owner()? != ctx.posix_thread.credentials().fsuid() {
+        return_errno_with_message!(Errno::EPERM, "the core file is owned by another user");
+    }
+    file.resize(0)?;
+
+    Ok(Arc::new(file))
+}
+
+/// A writer that writes core files without exceeding the size limit.
+struct CoreWriter {
+    file: Arc<dyn FileLike>,
+    written: u64,
+    limit: u64,
+}
+
+impl CoreWriter {
+    fn write(&mut self, buf: &[u8]) -> Result<()> {
+        // Like Linux, the dump is aborted if the limit is exceeded.
+        if self.written + buf.len() as u64 > self.limit {
+            return_errno_with_message!(Errno::EFBIG, "the core file size limit is exceeded");
+        }
+
+        if self.file.write_bytes(buf)? != buf.len() {
+            return_errno_with_message!(Errno::EIO, "the core file is not completely written");
+        }
+        self.written += buf.len() as u64;
+        Ok(())
+    }
+}
+
+/// Writes the core file.
+///
+/// The core file consists of the ELF header, the program headers, the notes, and the contents
+/// of the memory mappings, each of which is described by a `PT_LOAD` program header.
+fn write_core(
+    writer: &mut CoreWriter,
+    ctx: &Context,
+    user_ctx: &UserContext,
+    siginfo: &siginfo_t,
+) -> Result<()> {
+    // The other threads are still running, so the memory may change during the dump. This is
+    // acceptable since they will be killed soon and their states are not dumped anyway.
+    let root_vmar = ctx.thread_local.root_vmar();
+    let mut mappings = root_vmar.mappings();
+    mappings.truncate(MAX_LOAD_SEGMENTS);
+    let notes = notes(ctx, user_ctx, siginfo);
+
+    let phnum = mappings.len() + 1;
+    let notes_offset = size_of::<Elf64Ehdr>() + size_of::<Elf64Phdr>() * phnum;
+    let data_offset = (notes_offset + notes.len()).align_up(PAGE_SIZE);
+
+    writer.write(Elf64Ehdr::new_core(phnum as u16).as_bytes())?;
+    let notes_phdr = Elf64Phdr {
+        p_type: PT_NOTE,
+        p_offset: notes_offset as u64,
+        p_filesz: notes.len() as u64,
+        p_align: 4,
+        ..Pod::new_zeroed()
+    };
+    writer.write(notes_phdr.as_bytes())?;
+
+    let mut offset = data_offset;
+    for (range, perms) in mappings.iter() {
+        let file_size = dump_size(range, *perms);
+        let load_phdr = Elf64Phdr {
+            p_type: PT_LOAD,
+            p_flags: segment_flags(*perms),
+            p_offset: offset as u64,
+            p_vaddr: range.start as u64,
+            p_paddr: 0,
+            p_filesz: file_size as u64,
+            p_memsz: range.len() as u64,
+            p_align: PAGE_SIZE as u64,
+        };
+        writer.write(load_phdr.as_bytes())?;
+        offset += file_size;
+    }
+
+    writer.write(&notes)?;
+    writer.write(&vec![0u8; data_offset - notes_offset - notes.len()])?;
+
+    let mut page = vec![0u8; PAGE_SIZE];
+    for (range, perms) in mappings {
+        if dump_size(&range, perms) == 0 {
+            continue;
+        }
+        for page_addr in range.step_by(PAGE_SIZE) {
+            // Like Linux, the pages that cannot be read (e.g., beyond the end of the mapped
+            // file) are filled with zeros.
+            if root_vmar.read_page_for_dump(page_addr, &mut page).is_err() {
+                page.fill(0);
+            }
+            writer.write(&page)?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Returns the size of the mapping contents in the core file.
+fn dump_size(range: &Range<Vaddr>, perms: VmPerms) -> usize {
+    if perms.contains(VmPerms::READ) {
+        range.len()
+    } else {
+        0
+    }
+}
+
+fn segment_flags(perms: VmPerms) -> u32 {
+    let mut flags = 0;
+    if perms.contains(VmPerms::READ) {
+        flags |= PF_R;
+    }
+    if perms.contains(VmPerms::WRITE) {
+        flags |= PF_W;
+    }
+    if perms.contains(VmPerms::EXEC) {
+        flags |= PF_X;
+    }
+    flags
+}
+
+/// Returns the notes that describe the process and the current thread.
+///
+/// Only the registers of the current thread can be dumped, since the registers of the other
+/// threads are unavailable while they are running.
+fn notes(ctx: &Context, user_ctx: &UserContext, siginfo: &siginfo_t) -> Vec<u8> {
+    let process = ctx.process;
+    let posix_thread = ctx.posix_thread;
+    let pid_ns = process.pid_ns();
+    let local_id = |id| pid_ns.local_id(id).unwrap_or(0) as i32;
+
+    let pid = process.ns_pid() as i32;
+    let ppid = local_id(process.parent().pid());
+    let pgrp = local_id(process.pgid());
+    let sid = process
+        .session()
+        .map_or(0, |session| local_id(session.sid()));
+
+    let prof_clock = process.prof_clock();
+    let prstatus = ElfPrStatus {
+        info: ElfSigInfo {
+            si_signo: siginfo.si_signo,
+            si_code: siginfo.si_code,
+            si_errno: siginfo.si_errno,
+        },
+        pr_cursig: siginfo.si_signo as i16,
+        pr_sigpend: posix_thread.sig_pending().into(),
+        pr_sighold: posix_thread.sig_mask().load(Ordering::Relaxed).into(),
+        pr_pid: posix_thread.ns_tid() as i32,
+        pr_ppid: ppid,
+        pr_pgrp: pgrp,
+        pr_sid: sid,
+        pr_utime: prof_clock.user_clock().read_time().into(),
+        pr_stime: prof_clock.kernel_clock().read_time().into(),
+        pr_reg: PtraceRegs::from_context(user_ctx, None),
+        ..Pod::new_zeroed()
+    };
+
+    let credentials = posix_thread.credentials();
+    let mut prpsinfo = ElfPrPsInfo {
+        pr_sname: b'R',
+        pr_nice: process.nice().load(Ordering::Relaxed).range().get(),
+        pr_uid: credentials.ruid().into(),
+        pr_gid: credentials.rgid().into(),
+        pr_pid: pid,
+        pr_ppid: ppid,
+        pr_pgrp: pgrp,
+        pr_sid: sid,
+        ..Pod::new_zeroed()
+    };
+    copy_truncated(thread_name(ctx).as_bytes(), &mut prpsinfo.pr_fname);
+    if let Ok(argv) = process.vm().init_stack_reader().argv() {
+        let args = argv
+            .iter()
+            .map(|arg| arg.to_string_lossy())
+            .collect::<Vec<_>>()
+            .join(" ");
+        copy_truncated(args.as_bytes(), &mut prpsinfo.pr_psargs);
+    }
+
+    let mut notes = Vec::new();
+    push_note(&mut notes, NT_PRSTATUS, prstatus.as_bytes());
+    push_note(&mut notes, NT_PRPSINFO, prpsinfo.as_bytes());
+    push_note(&mut notes, NT_SIGINFO, siginfo.as_bytes());
+    if let Ok(auxv) = process.vm().init_stack_reader().auxv() {
+        push_note(&mut notes, NT_AUXV, &auxv);
+    }
+    notes
+}
+
+/// Copies the bytes to the C string buffer, which is always terminated by zero.
+fn copy_truncated(src: &[u8], dst: &mut [u8]) {
+    let len = src.len().min(dst.len() - 1);
+    dst[..len].copy_from_slice(&src[..len]);
+}
+
+fn push_note(notes: &mut Vec<u8>, note_type: u32, desc: &[u8]) {
+    const NOTE_NAME: &[u8] = b"CORE\0";
+
+    let header = Elf64Nhdr {
+        n_namesz: NOTE_NAME.len() as u32,
+        n_descsz: desc.len() as u32,
+        n_type: note_type,
+    };
+    notes.extend_from_slice(header.as_bytes());
+    notes.extend_from_slice(NOTE_NAME);
+    notes.resize(notes.len().align_up(4), 0);
+    notes.extend_from_slice(desc);
+    notes.resize(notes.len().align_up(4), 0);
+}
+
+const ET_CORE: u16 = 4;
+#[cfg(target_arch = "x86_64")]
+const EM_CURRENT: u16 = 62; // EM_X86_64
+#[cfg(target_arch = "riscv64")]
+const EM_CURRENT: u16 = 243; // EM_RISCV
+#[cfg(target_arch = "aarch64")]
+const EM_CURRENT: u16 = 183; // EM_AARCH64
+
+const PT_LOAD: u32 = 1;
+const PT_NOTE: u32 = 4;
+const PF_X: u32 = 1;
+const PF_W: u32 = 2;
+const PF_R: u32 = 4;
+
+/// The maximum number of `PT_LOAD` segments, which keeps `e_phnum` below `PN_XNUM`.
+const MAX_LOAD_SEGMENTS: usize = 0xfffe;
+
+const NT_PRSTATUS: u32 = 1;
+const NT_PRPSINFO: u32 = 3;
+const NT_AUXV: u32 = 6;
+const NT_SIGINFO: u32 = 0x53494749;
+
+#[derive(Debug, Clone, Copy, Pod)]
+#[repr(C)]
+struct Elf64Ehdr {
+    e_ident: [u8; 16],
+    e_type: u16,
+    e_machine: u16,
+    e_version: u32,
+    e_entry: u64,
+    e_phoff: u64,
+    e_shoff: u64,
+    e_flags: u32,
+    e_ehsize: u16,
+    e_phentsize: u16,
+    e_phnum: u16,
+    e_shentsize: u16,
+    e_shnum: u16,
+    e_shstrndx: u16,
+}
+
+impl Elf64Ehdr {
+    fn new_core(phnum: u16) -> Self {
+        // The magic number, `ELFCLASS64`, `ELFDATA2LSB`, and `EV_CURRENT`.
+        let mut e_ident = [0u8; 16];
+        e_ident[..7].copy_from_slice(b"\x7fELF\x02\x01\x01");
+
+        Self {
+            e_ident,
+            e_type: ET_CORE,
+            e_machine: EM_CURRENT,
+            e_version: 1,
+            e_phoff: size_of::<Self>() as u64,
+            e_ehsize: size_of::<Self>() as u16,
+            e_phentsize: size_of::<Elf64Phdr>() as u16,
+            e_phnum: phnum,
+            ..Pod::new_zeroed()
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Pod)]
+#[repr(C)]
+struct Elf64Phdr {
+    p_type: u32,
+    p_flags: u32,
+    p_offset: u64,
+    p_vaddr: u64,
+    p_paddr: u64,
+    p_filesz: u64,
+    p_memsz: u64,
+    p_align: u64,
+}
+
+#[derive(Debug, Clone, Copy, Pod)]
+#[repr(C)]
+struct Elf64Nhdr {
+    n_namesz: u32,
+    n_descsz: u32,
+    n_type: u32,
+}
+
+/// The signal information in [`ElfPrStatus`] (`struct elf_siginfo`).
+#[derive(Debug, Clone, Copy, Pod)]
+#[repr(C)]
+struct ElfSigInfo {
+    si_signo: i32,
+    si_code: i32,
+    si_errno: i32,
+}
+
+/// The status of a thread (`struct elf_prstatus`).
+#[derive(Debug, Clone, Copy, Pod)]
+#[repr(C)]
+struct ElfPrStatus {
+    info: ElfSigInfo,
+    pr_cursig: i16,
+    _pad0: u16,
+    pr_sigpend: u64,
+    pr_sighold: u64,
+    pr_pid: i32,
+    pr_ppid: i32,
+    pr_pgrp: i32,
+    pr_sid: i32,
+    pr_utime: timeval_t,
+    pr_stime: timeval_t,
+    pr_cutime: timeval_t,
+    pr_cstime: timeval_t,
+    pr_reg: PtraceRegs,
+    pr_fpvalid: i32,
+    _pad1: u32,
+}
+
+/// The information of a process (`struct elf_prpsinfo`).
+#[derive(Debug, Clone, Copy, Pod)]
+#[repr(C)]
+struct ElfPrPsInfo {
+    pr_state: u8,
+    pr_sname: u8,
+    pr_zomb: u8,
+    pr_nice: i8,
+    _pad0: u32,
+    pr_flag: u64,
+    pr_uid: u32,
+    pr_gid: u32,
+    pr_pid: i32,
+    pr_ppid: i32,
+    pr_pgrp: i32,
+    pr_sid: i32,
+    pr_fname: [u8; 16],
+    pr_psargs: [u8; 80],
+}
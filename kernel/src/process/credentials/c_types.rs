@@ -18,4 +18,6 @@ pub struct cap_user_data_t {
     pub inheritable: u32,
 }
 
+pub const LINUX_CAPABILITY_VERSION_1: u32 = 0x19980330;
+pub const LINUX_CAPABILITY_VERSION_2: u32 = 0x20071026;
 pub const LINUX_CAPABILITY_VERSION_3: u32 = 0x20080522;
@@ -56,6 +56,19 @@ bitflags! {
 impl CapSet {
     const MASK: u64 = (1 << (CapSet::most_significant_bit() + 1)) - 1;
 
+    /// The capabilities that are related to the file system.
+    ///
+    /// They are cleared from or restored to the effective capabilities when the file system
+    /// user ID is changed from or to root.
+    pub const FS_MASK: Self = Self::CHOWN
+        .union(Self::DAC_OVERRIDE)
+        .union(Self::DAC_READ_SEARCH)
+        .union(Self::FOWNER)
+        .union(Self::FSETID)
+        .union(Self::LINUX_IMMUTABLE)
+        .union(Self::MKNOD)
+        .union(Self::MAC_OVERRIDE);
+
     /// Converts the capability set to a `u32`. The higher bits are truncated.
     pub fn as_u32(&self) -> u32 {
         self.bits() as u32
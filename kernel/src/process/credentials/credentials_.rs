@@ -47,6 +47,13 @@ pub(super) struct Credentials_ {
     /// Capability that we can actually use
     effective_capset: AtomicCapSet,
 
+    /// Capabilities that are preserved across `execve` for unprivileged programs.
+    /// It is always a subset of both the permitted and the inheritable capabilities.
+    ambient_capset: AtomicCapSet,
+
+    /// Capabilities that can ever be gained, e.g., by executing a set-user-ID-root program.
+    bounding_capset: AtomicCapSet,
+
     /// Keep capabilities flag
     keep_capabilities: AtomicBool,
 }
@@ -67,15 +74,17 @@ impl Credentials_ {
             sgid: AtomicGid::new(gid),
             fsgid: AtomicGid::new(gid),
             supplementary_gids: RwLock::new(supplementary_gids),
-            inheritable_capset: AtomicCapSet::new(capset),
+            inheritable_capset: AtomicCapSet::new(CapSet::empty()),
             permitted_capset: AtomicCapSet::new(capset),
             effective_capset: AtomicCapSet::new(capset),
+            ambient_capset: AtomicCapSet::new(CapSet::empty()),
+            bounding_capset: AtomicCapSet::new(CapSet::all()),
             keep_capabilities: AtomicBool::new(false),
         }
     }
 
-    fn is_privileged(&self) -> bool {
-        self.euid.is_root()
+    fn has_capability(&self, capability: CapSet) -> bool {
+        self.effective_capset().contains(capability)
     }
 
     //  ******* Uid methods *******
@@ -101,7 +110,9 @@ impl Credentials_ {
     }
 
     pub(super) fn set_uid(&self, uid: Uid) {
-        if self.is_privileged() {
+        let old_uids = self.uids();
+
+        if self.has_capability(CapSet::SETUID) {
             self.ruid.store(uid, Ordering::Relaxed);
             self.euid.store(uid, Ordering::Relaxed);
             self.suid.store(uid, Ordering::Relaxed);
@@ -118,17 +129,15 @@ impl Credentials_ {
             self.euid.store(uid, Ordering::Relaxed);
             self.fsuid.store(uid, Ordering::Relaxed);
         }
-        if !self.keep_capabilities.load(Ordering::Relaxed) {
-            self.set_permitted_capset(CapSet::empty());
-            self.set_inheritable_capset(CapSet::empty());
-        }
-        // Always clear the effective capabilities when changing the UID
-        self.set_effective_capset(CapSet::empty());
+
+        self.fixup_capsets_after_setuid(old_uids);
     }
 
     pub(super) fn set_reuid(&self, ruid: Option<Uid>, euid: Option<Uid>) -> Result<()> {
         self.check_uid_perm(ruid.as_ref(), euid.as_ref(), None, false)?;
 
+        let old_uids = self.uids();
+
         let should_set_suid = ruid.is_some() || euid.is_some_and(|euid| euid != self.ruid());
 
         self.set_resuid_unchecked(ruid, euid, None);
@@ -142,6 +151,8 @@ impl Credentials_ {
         // the same as `euid`, but `setreuid` does not mention the `fsuid` should be set.
         self.fsuid.store(self.euid(), Ordering::Release);
 
+        self.fixup_capsets_after_setuid(old_uids);
+
         Ok(())
     }
 
@@ -153,10 +164,14 @@ impl Credentials_ {
     ) -> Result<()> {
         self.check_uid_perm(ruid.as_ref(), euid.as_ref(), suid.as_ref(), true)?;
 
+        let old_uids = self.uids();
+
         self.set_resuid_unchecked(ruid, euid, suid);
 
         self.fsuid.store(self.euid(), Ordering::Release);
 
+        self.fixup_capsets_after_setuid(old_uids);
+
         Ok(())
     }
 
@@ -167,12 +182,11 @@ impl Credentials_ {
             return Ok(old_fsuid);
         };
 
-        if self.is_privileged() {
-            self.fsuid.store(fsuid, Ordering::Release);
-            return Ok(old_fsuid);
-        }
-
-        if fsuid != self.ruid() && fsuid != self.euid() && fsuid != self.suid() {
+        if !self.has_capability(CapSet::SETUID)
+            && fsuid != self.ruid()
+            && fsuid != self.euid()
+            && fsuid != self.suid()
+        {
             return_errno_with_message!(
                 Errno::EPERM,
                 "fsuid can only be one of old ruid, old euid and old suid."
@@ -180,6 +194,7 @@ impl Credentials_ {
         }
 
         self.fsuid.store(fsuid, Ordering::Release);
+        self.fixup_fs_capabilities(old_fsuid);
 
         Ok(old_fsuid)
     }
@@ -201,7 +216,7 @@ impl Credentials_ {
         suid: Option<&Uid>,
         ruid_may_be_old_suid: bool,
     ) -> Result<()> {
-        if self.is_privileged() {
+        if self.has_capability(CapSet::SETUID) {
             return Ok(());
         }
 
@@ -255,6 +270,52 @@ impl Credentials_ {
         }
     }
 
+    fn uids(&self) -> [Uid; 4] {
+        [self.ruid(), self.euid(), self.suid(), self.fsuid()]
+    }
+
+    /// Updates the capabilities after the user IDs are changed.
+    ///
+    /// Like Linux, the capabilities are adjusted so that the programs which assume that
+    /// the root user is privileged still work:
+    /// - If any of the real, effective and saved user IDs was root and none of them is root
+    ///   now, the permitted, effective and ambient capabilities are cleared, unless the keep
+    ///   capabilities flag is set.
+    /// - If the effective user ID changes from root to non-root, the effective capabilities
+    ///   are cleared. In the reverse direction, the permitted capabilities become effective.
+    /// - The file system capabilities are adjusted according to the file system user ID.
+    ///
+    /// Reference: <https://man7.org/linux/man-pages/man7/capabilities.7.html>.
+    fn fixup_capsets_after_setuid(&self, old_uids: [Uid; 4]) {
+        let [old_ruid, old_euid, old_suid, old_fsuid] = old_uids;
+
+        let had_root = old_ruid.is_root() || old_euid.is_root() || old_suid.is_root();
+        let has_root = self.ruid.is_root() || self.euid.is_root() || self.suid.is_root();
+        if had_root && !has_root && !self.keep_capabilities() {
+            self.set_permitted_capset(CapSet::empty());
+            self.set_effective_capset(CapSet::empty());
+            self.set_ambient_capset(CapSet::empty());
+        }
+
+        if old_euid.is_root() && !self.euid.is_root() {
+            self.set_effective_capset(CapSet::empty());
+        } else if !old_euid.is_root() && self.euid.is_root() {
+            self.set_effective_capset(self.permitted_capset());
+        }
+
+        self.fixup_fs_capabilities(old_fsuid);
+    }
+
+    /// Updates the file system capabilities after the file system user ID is changed.
+    fn fixup_fs_capabilities(&self, old_fsuid: Uid) {
+        let effective = self.effective_capset();
+        if old_fsuid.is_root() && !self.fsuid.is_root() {
+            self.set_effective_capset(effective - CapSet::FS_MASK);
+        } else if !old_fsuid.is_root() && self.fsuid.is_root() {
+            self.set_effective_capset(effective | (self.permitted_capset() & CapSet::FS_MASK));
+        }
+    }
+
     //  ******* Gid methods *******
 
     pub(super) fn rgid(&self) -> Gid {
@@ -274,7 +335,7 @@ impl Credentials_ {
     }
 
     pub(super) fn set_gid(&self, gid: Gid) {
-        if self.is_privileged() {
+        if self.has_capability(CapSet::SETGID) {
             self.rgid.store(gid, Ordering::Relaxed);
             self.egid.store(gid, Ordering::Relaxed);
             self.sgid.store(gid, Ordering::Relaxed);
@@ -323,7 +384,7 @@ impl Credentials_ {
             return Ok(old_fsgid);
         };
 
-        if self.is_privileged() {
+        if self.has_capability(CapSet::SETGID) {
             self.fsgid.store(fsgid, Ordering::Relaxed);
             return Ok(old_fsgid);
         }
@@ -362,7 +423,7 @@ impl Credentials_ {
         sgid: Option<&Gid>,
         rgid_may_be_old_sgid: bool,
     ) -> Result<()> {
-        if self.is_privileged() {
+        if self.has_capability(CapSet::SETGID) {
             return Ok(());
         }
 
@@ -454,6 +515,56 @@ impl Credentials_ {
         self.effective_capset
             .store(effective_capset, Ordering::Relaxed);
     }
+
+    pub(super) fn ambient_capset(&self) -> CapSet {
+        self.ambient_capset.load(Ordering::Relaxed)
+    }
+
+    pub(super) fn set_ambient_capset(&self, ambient_capset: CapSet) {
+        self.ambient_capset.store(ambient_capset, Ordering::Relaxed);
+    }
+
+    pub(super) fn bounding_capset(&self) -> CapSet {
+        self.bounding_capset.load(Ordering::Relaxed)
+    }
+
+    pub(super) fn set_bounding_capset(&self, bounding_capset: CapSet) {
+        self.bounding_capset
+            .store(bounding_capset, Ordering::Relaxed);
+    }
+
+    /// Computes the capabilities of the new program after `execve`.
+    ///
+    /// File capabilities are not supported, so the capabilities are computed as if the program
+    /// has no file capabilities. Like Linux, if the real or effective user ID is root, the
+    /// program is treated as if it has all the file capabilities, so that the root user gets
+    /// all the capabilities in the bounding set. Otherwise, only the ambient capabilities are
+    /// preserved.
+    ///
+    /// `is_privileged_exec` should be true if the user IDs or group IDs are changed by the
+    /// set-user-ID or set-group-ID bits, in which case the ambient capabilities are cleared.
+    ///
+    /// Reference: <https://man7.org/linux/man-pages/man7/capabilities.7.html>.
+    pub(super) fn apply_exec_capsets(&self, is_privileged_exec: bool) {
+        if is_privileged_exec {
+            self.set_ambient_capset(CapSet::empty());
+        }
+        let ambient = self.ambient_capset();
+
+        let permitted = if self.ruid.is_root() || self.euid.is_root() {
+            self.inheritable_capset() | self.bounding_capset() | ambient
+        } else {
+            ambient
+        };
+        let effective = if self.euid.is_root() {
+            permitted
+        } else {
+            ambient
+        };
+
+        self.set_permitted_capset(permitted);
+        self.set_effective_capset(effective);
+    }
 }
 
 impl Clone for Credentials_ {
@@ -471,6 +582,8 @@ impl Clone for Credentials_ {
             inheritable_capset: self.inheritable_capset.clone(),
             permitted_capset: self.permitted_capset.clone(),
             effective_capset: self.effective_capset.clone(),
+            ambient_capset: self.ambient_capset.clone(),
+            bounding_capset: self.bounding_capset.clone(),
             keep_capabilities: AtomicBool::new(self.keep_capabilities.load(Ordering::Relaxed)),
         }
     }
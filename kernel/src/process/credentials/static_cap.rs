@@ -316,4 +316,44 @@ impl<R: TRights> Credentials<R> {
     pub fn set_effective_capset(&self, effective_capset: CapSet) {
         self.0.set_effective_capset(effective_capset);
     }
+
+    /// Gets the capabilities that are preserved across `execve` for unprivileged programs.
+    ///
+    /// This method requires the `Read` right.
+    #[require(R > Read)]
+    pub fn ambient_capset(&self) -> CapSet {
+        self.0.ambient_capset()
+    }
+
+    /// Sets the capabilities that are preserved across `execve` for unprivileged programs.
+    ///
+    /// This method requires the `Write` right.
+    #[require(R > Write)]
+    pub fn set_ambient_capset(&self, ambient_capset: CapSet) {
+        self.0.set_ambient_capset(ambient_capset);
+    }
+
+    /// Gets the capabilities that can ever be gained.
+    ///
+    /// This method requires the `Read` right.
+    #[require(R > Read)]
+    pub fn bounding_capset(&self) -> CapSet {
+        self.0.bounding_capset()
+    }
+
+    /// Sets the capabilities that can ever be gained.
+    ///
+    /// This method requires the `Write` right.
+    #[require(R > Write)]
+    pub fn set_bounding_capset(&self, bounding_capset: CapSet) {
+        self.0.set_bounding_capset(bounding_capset);
+    }
+
+    /// Computes the capabilities of the new program after `execve`.
+    ///
+    /// This method requires the `Write` right.
+    #[require(R > Write)]
+    pub fn apply_exec_capsets(&self, is_privileged_exec: bool) {
+        self.0.apply_exec_capsets(is_privileged_exec);
+    }
 }
@@ -1,7 +1,14 @@
 // SPDX-License-Identifier: MPL-2.0
 
-use super::{posix_thread::ThreadLocal, process_table, Pid, Process};
-use crate::{prelude::*, process::signal::signals::kernel::KernelSignal};
+use super::{
+    posix_thread::{ptrace::detach_tracees, AsPosixThread, ThreadLocal},
+    process_table, Process,
+};
+use crate::{
+    events::IoEvents,
+    prelude::*,
+    process::signal::{constants::SIGKILL, signals::kernel::KernelSignal},
+};
 
 /// Exits the current POSIX process.
 ///
@@ -13,15 +20,42 @@ use crate::{prelude::*, process::signal::signals::kernel::KernelSignal};
 pub(super) fn exit_process(thread_local: &ThreadLocal, current_process: &Process) {
     current_process.status().set_zombie();
 
+    current_process.timer_manager().clear_posix_timers();
+
+    undo_semaphore_operations(current_process);
+
     // FIXME: This is obviously wrong in a number of ways, since different threads can have
     // different file tables, and different processes can share the same file table.
     thread_local.file_table().borrow().write().close_all();
 
     send_parent_death_signal(current_process);
 
-    move_children_to_init(current_process);
+    if current_process.is_init_process() {
+        kill_pid_ns_processes(current_process);
+    }
+
+    detach_tracees(current_process);
+
+    move_children_to_reaper(current_process);
 
     send_child_death_signal(current_process);
+
+    current_process.pidfd_pollee().notify(IoEvents::IN);
+}
+
+/// Undoes the System V semaphore operations with `SEM_UNDO`.
+//
+// FIXME: The threads in a process may be in different IPC namespaces. The operations in the IPC
+// namespace of the last thread are undone here.
+fn undo_semaphore_operations(current_process: &Process) {
+    let ipc_ns = current_thread!()
+        .as_posix_thread()
+        .unwrap()
+        .ns_proxy()
+        .lock()
+        .ipc_ns()
+        .clone();
+    ipc_ns.sem_sets().undo_adjustments(current_process.pid());
 }
 
 /// Sends parent-death signals to the children.
@@ -41,21 +75,52 @@ fn send_parent_death_signal(current_process: &Process) {
     }
 }
 
-/// Moves the children to the init process.
-fn move_children_to_init(current_process: &Process) {
-    if is_init_process(current_process) {
+/// Kills all the other processes in the PID namespace whose init process is exiting.
+///
+/// No new processes can be created in the PID namespace afterwards.
+fn kill_pid_ns_processes(current_process: &Process) {
+    let pid_ns = current_process.pid_ns();
+    // The processes in the initial PID namespace are not killed when the init process of the
+    // system exits.
+    if pid_ns.parent().is_none() {
         return;
     }
 
-    let Some(init_process) = get_init_process() else {
+    pid_ns.set_dead();
+
+    for process in process_table::process_table_mut().iter() {
+        if process.pid() != current_process.pid() && pid_ns.local_id(process.pid()).is_some() {
+            process.enqueue_signal(KernelSignal::new(SIGKILL));
+        }
+    }
+}
+
+/// Moves the children to the reaper.
+fn move_children_to_reaper(current_process: &Process) {
+    let Some(reaper) = find_reaper(current_process) else {
         return;
     };
 
-    let mut init_children = init_process.children().lock();
+    let mut has_zombie_child = false;
+    let mut reaper_children = reaper.children().lock();
     for (_, child_process) in current_process.children().lock().extract_if(|_, _| true) {
         let mut parent = child_process.parent.lock();
-        init_children.insert(child_process.pid(), child_process.clone());
-        parent.set_process(&init_process);
+        reaper_children.insert(child_process.pid(), child_process.clone());
+        parent.set_process(&reaper);
+
+        // The reaper is notified of the children that have already exited, since they may never
+        // be reaped otherwise.
+        if child_process.status().is_zombie() {
+            if let Some(signal) = child_process.exit_signal().map(KernelSignal::new) {
+                reaper.enqueue_signal(signal);
+            }
+            has_zombie_child = true;
+        }
+    }
+    drop(reaper_children);
+
+    if has_zombie_child {
+        reaper.children_wait_queue().wake_all();
     }
 }
 
@@ -71,13 +136,32 @@ fn send_child_death_signal(current_process: &Process) {
     parent.children_wait_queue().wake_all();
 }
 
-const INIT_PROCESS_PID: Pid = 1;
+/// Finds the process that adopts the orphaned children.
+///
+/// The reaper is the nearest living ancestor that is a child subreaper in the PID namespace of the
+/// current process. If there is no such ancestor, the reaper is the init process of the PID
+/// namespace. If the current process is the init process itself or the init process has exited,
+/// the reaper is the init process of the parent PID namespace.
+fn find_reaper(current_process: &Process) -> Option<Arc<Process>> {
+    if let Some(subreaper) = find_child_subreaper(current_process) {
+        return Some(subreaper);
+    }
 
-/// Gets the init process
-fn get_init_process() -> Option<Arc<Process>> {
-    process_table::get_process(INIT_PROCESS_PID)
+    core::iter::successors(Some(current_process.pid_ns()), |pid_ns| pid_ns.parent())
+        .filter_map(|pid_ns| pid_ns.init_process_id())
+        .filter(|pid| *pid != current_process.pid())
+        .filter_map(process_table::get_process)
+        .find(|process| !process.status().is_zombie())
 }
 
-fn is_init_process(process: &Process) -> bool {
-    process.pid() == INIT_PROCESS_PID
+/// Finds the nearest living ancestor that is a child subreaper.
+///
+/// The search stops at the init process of the PID namespace of the current process.
+fn find_child_subreaper(current_process: &Process) -> Option<Arc<Process>> {
+    let pid_ns = current_process.pid_ns();
+    let parent_of = |process: &Process| process.parent().lock().process().upgrade();
+
+    core::iter::successors(parent_of(current_process), |process| parent_of(process))
+        .take_while(|process| Arc::ptr_eq(process.pid_ns(), pid_ns) && !process.is_init_process())
+        .find(|process| process.is_child_subreaper() && !process.status().is_zombie())
 }
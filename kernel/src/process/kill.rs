@@ -1,6 +1,7 @@
 // SPDX-License-Identifier: MPL-2.0
 
 use super::{
+    credentials::capabilities::CapSet,
     posix_thread::{thread_table, AsPosixThread},
     process_table,
     signal::{
@@ -19,7 +20,7 @@ use crate::{prelude::*, thread::Tid};
 ///
 /// If `signal` is `None`, this method will only check permission without sending
 /// any signal.
-pub fn kill(pid: Pid, signal: Option<UserSignal>, ctx: &Context) -> Result<()> {
+pub fn kill<S: Signal + Copy>(pid: Pid, signal: Option<S>, ctx: &Context) -> Result<()> {
     // Fast path: If the signal is sent to self, we can skip most check.
     if pid == ctx.process.pid() {
         let Some(signal) = signal else {
@@ -65,9 +66,17 @@ pub fn kill_group(pgid: Pgid, signal: Option<UserSignal>, ctx: &Context) -> Resu
 /// Sends a signal to a target thread, using the current process
 /// as the sender.
 ///
+/// If `tgid` is `None`, the target thread can belong to any thread group, which is the
+/// behavior of `tkill`.
+///
 /// If `signal` is `None`, this method will only check permission without sending
 /// any signal.
-pub fn tgkill(tid: Tid, tgid: Pid, signal: Option<UserSignal>, ctx: &Context) -> Result<()> {
+pub fn tgkill<S: Signal + Copy>(
+    tid: Tid,
+    tgid: Option<Pid>,
+    signal: Option<S>,
+    ctx: &Context,
+) -> Result<()> {
     let thread = thread_table::get_thread(tid)
         .ok_or_else(|| Error::with_message(Errno::ESRCH, "target thread does not exist"))?;
 
@@ -79,10 +88,10 @@ pub fn tgkill(tid: Tid, tgid: Pid, signal: Option<UserSignal>, ctx: &Context) ->
 
     // Check tgid
     let pid = posix_thread.process().pid();
-    if pid != tgid {
+    if tgid.is_some_and(|tgid| tgid != pid) {
         return_errno_with_message!(
-            Errno::EINVAL,
-            "the combination of tgid and pid is not valid"
+            Errno::ESRCH,
+            "the target thread does not belong to the thread group"
         );
     }
 
@@ -101,12 +110,19 @@ pub fn tgkill(tid: Tid, tgid: Pid, signal: Option<UserSignal>, ctx: &Context) ->
 /// Sends a signal to all processes except current process and init process, using
 /// the current process as the sender.
 ///
+/// Only the processes that are visible in the PID namespace of the current process are sent
+/// the signal, and the init process refers to the one of that namespace.
+///
 /// The credentials of the current process will be checked to determine
 /// if it is authorized to send the signal to the target group.
 pub fn kill_all(signal: Option<UserSignal>, ctx: &Context) -> Result<()> {
     let current = current!();
+    let pid_ns = current.pid_ns();
     for process in process_table::process_table_mut().iter() {
-        if Arc::ptr_eq(&current, process) || process.is_init_process() {
+        if Arc::ptr_eq(&current, process)
+            || pid_ns.local_id(process.pid()).is_none()
+            || pid_ns.init_process_id() == Some(process.pid())
+        {
             continue;
         }
 
@@ -116,46 +132,40 @@ pub fn kill_all(signal: Option<UserSignal>, ctx: &Context) -> Result<()> {
     Ok(())
 }
 
-fn kill_process(process: &Process, signal: Option<UserSignal>, ctx: &Context) -> Result<()> {
+/// Sends a signal to a process, using the current process as the sender.
+///
+/// Unlike [`kill`], the target process is specified by reference rather than by PID, so the
+/// signal cannot be sent to another process that reuses the PID.
+///
+/// If `signal` is `None`, this method will only check permission without sending
+/// any signal.
+pub fn kill_process<S: Signal + Copy>(
+    process: &Process,
+    signal: Option<S>,
+    ctx: &Context,
+) -> Result<()> {
     let tasks = process.tasks().lock();
 
     let signum = signal.map(|signal| signal.num());
     let sender_ids = current_thread_sender_ids(signum.as_ref(), ctx);
 
-    let mut permitted_thread = None;
-    for task in tasks.as_slice() {
-        let posix_thread = task.as_posix_thread().unwrap();
-
-        // First check permission
-        if posix_thread
+    // Check whether any thread of the process permits the signal.
+    let is_permitted = tasks.as_slice().iter().any(|task| {
+        task.as_posix_thread()
+            .unwrap()
             .check_signal_perm(signum.as_ref(), &sender_ids)
             .is_ok()
-        {
-            let Some(ref signum) = signum else {
-                // If signal is None, only permission check is required
-                return Ok(());
-            };
-
-            if !posix_thread.has_signal_blocked(*signum) {
-                // Send signal to any thread that does not blocks the signal.
-                let signal = signal.unwrap();
-                posix_thread.enqueue_signal(Box::new(signal));
-                return Ok(());
-            } else if permitted_thread.is_none() {
-                permitted_thread = Some(posix_thread);
-            }
-        }
-    }
-
-    let Some(permitted_thread) = permitted_thread else {
+    });
+    if !is_permitted {
         return_errno_with_message!(Errno::EPERM, "cannot send signal to the target process");
-    };
+    }
 
     // If signal is None, only permission check is required
     let Some(signal) = signal else { return Ok(()) };
 
-    // If all threads block the signal, send signal to the first thread.
-    permitted_thread.enqueue_signal(Box::new(signal));
+    // The signal is shared by the threads and will be handled by any thread that does not block
+    // it.
+    process.enqueue_shared_signal(Box::new(signal), &tasks);
 
     Ok(())
 }
@@ -164,6 +174,7 @@ fn current_thread_sender_ids(signum: Option<&SigNum>, ctx: &Context) -> SignalSe
     let credentials = ctx.posix_thread.credentials();
     let ruid = credentials.ruid();
     let euid = credentials.euid();
+    let can_kill_any = credentials.effective_capset().contains(CapSet::KILL);
     let sid = signum.and_then(|signum| {
         if *signum == SIGCONT {
             Some(ctx.process.session().unwrap().sid())
@@ -172,21 +183,28 @@ fn current_thread_sender_ids(signum: Option<&SigNum>, ctx: &Context) -> SignalSe
         }
     });
 
-    SignalSenderIds::new(ruid, euid, sid)
+    SignalSenderIds::new(ruid, euid, can_kill_any, sid)
 }
 
 /// The ids of the signal sender process.
 ///
-/// This struct now includes effective user id, real user id and session id.
+/// This struct now includes effective user id, real user id, whether the sender has
+/// `CAP_KILL` and session id.
 pub(super) struct SignalSenderIds {
     ruid: Uid,
     euid: Uid,
+    can_kill_any: bool,
     sid: Option<Sid>,
 }
 
 impl SignalSenderIds {
-    fn new(ruid: Uid, euid: Uid, sid: Option<Sid>) -> Self {
-        Self { ruid, euid, sid }
+    fn new(ruid: Uid, euid: Uid, can_kill_any: bool, sid: Option<Sid>) -> Self {
+        Self {
+            ruid,
+            euid,
+            can_kill_any,
+            sid,
+        }
     }
 
     pub(super) fn ruid(&self) -> Uid {
@@ -197,6 +215,12 @@ impl SignalSenderIds {
         self.euid
     }
 
+    /// Returns whether the sender has `CAP_KILL`, which allows it to send signals to any
+    /// process.
+    pub(super) fn can_kill_any(&self) -> bool {
+        self.can_kill_any
+    }
+
     pub(super) fn sid(&self) -> Option<Sid> {
         self.sid
     }
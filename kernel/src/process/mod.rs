@@ -1,9 +1,15 @@
 // SPDX-License-Identifier: MPL-2.0
 
+pub mod cgroup;
 mod clone;
+pub mod coredump;
 pub mod credentials;
 mod exit;
 mod kill;
+pub mod namespace;
+pub mod oom;
+pub mod personality;
+mod pid_file;
 pub mod posix_thread;
 #[allow(clippy::module_inception)]
 mod process;
@@ -21,16 +27,18 @@ mod wait;
 
 pub use clone::{clone_child, CloneArgs, CloneFlags};
 pub use credentials::{Credentials, Gid, Uid};
-pub use kill::{kill, kill_all, kill_group, tgkill};
+pub use kill::{kill, kill_all, kill_group, kill_process, tgkill};
+pub use pid_file::PidFile;
 pub use process::{
-    ExitCode, JobControl, Pgid, Pid, Process, ProcessBuilder, ProcessGroup, Session, Sid, Terminal,
+    ExitCode, JobControl, Pgid, Pid, PosixTimer, Process, ProcessBuilder, ProcessGroup, Session,
+    Sid, Terminal,
 };
 pub use process_filter::ProcessFilter;
-pub use process_vm::{MAX_ARGV_NUMBER, MAX_ARG_LEN, MAX_ENVP_NUMBER, MAX_ENV_LEN};
-pub use program_loader::{check_executable_file, load_program_to_vm};
+pub use process_vm::{aslr, ProcessVm, MAX_ARGV_NUMBER, MAX_ARG_LEN, MAX_ENVP_NUMBER, MAX_ENV_LEN};
+pub use program_loader::{binfmt_misc, check_executable_file, load_program_to_vm, ExecIds};
 pub use rlimit::ResourceType;
 pub use term_status::TermStatus;
-pub use wait::{wait_child_exit, WaitOptions};
+pub use wait::{wait_child_exit, WaitOptions, WaitedChild};
 
 pub(super) fn init() {
     process::init();
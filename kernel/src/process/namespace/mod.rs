@@ -0,0 +1,17 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! Namespaces.
+//!
+//! A namespace wraps a global system resource in an abstraction that makes it
+//! appear to the threads within the namespace that they have their own isolated
+//! instance of the resource.
+//!
+//! Reference: <https://man7.org/linux/man-pages/man7/namespaces.7.html>.
+
+mod nsproxy;
+mod pid;
+mod uts;
+
+pub use nsproxy::{Namespace, NsProxy};
+pub use pid::PidNamespace;
+pub use uts::UtsNamespace;
@@ -0,0 +1,149 @@
+// SPDX-License-Identifier: MPL-2.0
+
+use spin::Once;
+
+use super::{PidNamespace, UtsNamespace};
+use crate::{
+    fs::{
+        fs_resolver::FsResolver,
+        path::{Dentry, MountNamespace},
+    },
+    ipc::IpcNamespace,
+    prelude::*,
+    process::CloneFlags,
+};
+
+/// The set of namespaces that a POSIX thread belongs to.
+///
+/// An `NsProxy` is immutable. Creating or entering a namespace replaces the
+/// `NsProxy` of the thread with a new one.
+///
+/// The PID namespace of a thread is fixed when the thread is created, so the
+/// `NsProxy` only holds the PID namespace for the children of the thread.
+#[derive(Clone)]
+pub struct NsProxy {
+    uts_ns: Arc<UtsNamespace>,
+    ipc_ns: Arc<IpcNamespace>,
+    mnt_ns: Arc<MountNamespace>,
+    pid_ns_for_children: Arc<PidNamespace>,
+}
+
+/// A namespace of any type.
+#[derive(Clone)]
+pub enum Namespace {
+    Uts(Arc<UtsNamespace>),
+    Ipc(Arc<IpcNamespace>),
+    Mnt(Arc<MountNamespace>),
+    Pid(Arc<PidNamespace>),
+}
+
+impl Namespace {
+    /// Returns the `CLONE_NEW*` flag that creates the type of the namespace.
+    pub fn clone_flag(&self) -> CloneFlags {
+        match self {
+            Self::Uts(_) => CloneFlags::CLONE_NEWUTS,
+            Self::Ipc(_) => CloneFlags::CLONE_NEWIPC,
+            Self::Mnt(_) => CloneFlags::CLONE_NEWNS,
+            Self::Pid(_) => CloneFlags::CLONE_NEWPID,
+        }
+    }
+}
+
+impl NsProxy {
+    /// Gets the `NsProxy` of the initial namespaces.
+    pub fn get_init_singleton() -> &'static Arc<NsProxy> {
+        static INIT: Once<Arc<NsProxy>> = Once::new();
+
+        INIT.call_once(|| {
+            Arc::new(Self {
+                uts_ns: UtsNamespace::get_init_singleton().clone(),
+                ipc_ns: IpcNamespace::get_init_singleton().clone(),
+                mnt_ns: MountNamespace::get_init_singleton().clone(),
+                pid_ns_for_children: PidNamespace::get_init_singleton().clone(),
+            })
+        })
+    }
+
+    /// Gets the UTS namespace.
+    pub fn uts_ns(&self) -> &Arc<UtsNamespace> {
+        &self.uts_ns
+    }
+
+    /// Gets the IPC namespace.
+    pub fn ipc_ns(&self) -> &Arc<IpcNamespace> {
+        &self.ipc_ns
+    }
+
+    /// Gets the mount namespace.
+    pub fn mnt_ns(&self) -> &Arc<MountNamespace> {
+        &self.mnt_ns
+    }
+
+    /// Gets the PID namespace for the children.
+    pub fn pid_ns_for_children(&self) -> &Arc<PidNamespace> {
+        &self.pid_ns_for_children
+    }
+
+    /// Creates the `NsProxy` for a new thread or a thread calling `unshare(2)`
+    /// according to the `CLONE_NEW*` flags.
+    ///
+    /// The `resolver` must be owned exclusively by the new thread (or the unsharing
+    /// thread). It will be updated to refer to the new mount namespace if
+    /// `CLONE_NEWNS` is specified.
+    pub fn copy_with_flags(
+        self: &Arc<Self>,
+        flags: CloneFlags,
+        resolver: &mut FsResolver,
+    ) -> Result<Arc<Self>> {
+        if !flags.intersects(Self::supported_flags()) {
+            return Ok(self.clone());
+        }
+
+        let mut new_ns_proxy = self.as_ref().clone();
+        if flags.contains(CloneFlags::CLONE_NEWUTS) {
+            new_ns_proxy.uts_ns = self.uts_ns.copy();
+        }
+        if flags.contains(CloneFlags::CLONE_NEWIPC) {
+            new_ns_proxy.ipc_ns = IpcNamespace::new();
+        }
+        if flags.contains(CloneFlags::CLONE_NEWNS) {
+            new_ns_proxy.mnt_ns = self.mnt_ns.copy(resolver);
+        }
+        if flags.contains(CloneFlags::CLONE_NEWPID) {
+            new_ns_proxy.pid_ns_for_children = self.pid_ns_for_children.new_child()?;
+        }
+
+        Ok(Arc::new(new_ns_proxy))
+    }
+
+    /// Creates the `NsProxy` for a thread calling `setns(2)` to enter the namespace.
+    ///
+    /// The `resolver` must be owned exclusively by the thread. Its root and current
+    /// working directory will be moved to the root of the mount namespace if a mount
+    /// namespace is entered.
+    ///
+    /// Entering a PID namespace only changes the PID namespace for the children.
+    pub fn enter(self: &Arc<Self>, ns: Namespace, resolver: &mut FsResolver) -> Arc<Self> {
+        let mut new_ns_proxy = self.as_ref().clone();
+        match ns {
+            Namespace::Uts(uts_ns) => new_ns_proxy.uts_ns = uts_ns,
+            Namespace::Ipc(ipc_ns) => new_ns_proxy.ipc_ns = ipc_ns,
+            Namespace::Mnt(mnt_ns) => {
+                resolver.set_root(Dentry::new_fs_root(mnt_ns.root().clone()));
+                resolver.set_cwd(Dentry::new_fs_root(mnt_ns.root().clone()));
+                new_ns_proxy.mnt_ns = mnt_ns;
+            }
+            Namespace::Pid(pid_ns) => new_ns_proxy.pid_ns_for_children = pid_ns,
+        }
+
+        Arc::new(new_ns_proxy)
+    }
+
+    /// Returns the `CLONE_NEW*` flags that are supported.
+    pub fn supported_flags() -> CloneFlags {
+        CloneFlags::CLONE_NEWNS
+            | CloneFlags::CLONE_NEWUTS
+            | CloneFlags::CLONE_NEWIPC
+            | CloneFlags::CLONE_NEWPID
+    }
+}
@@ -0,0 +1,172 @@
+// SPDX-License-Identifier: MPL-2.0
+
+use spin::Once;
+
+use crate::{prelude::*, process::Pid};
+
+/// The maximum nesting depth of PID namespaces, which is the same as Linux.
+const MAX_LEVEL: usize = 32;
+
+/// The PID of the init process in a PID namespace.
+const INIT_PID: Pid = 1;
+
+/// A PID namespace.
+///
+/// A PID namespace isolates the process ID number space, so processes in different PID
+/// namespaces can have the same PID. A process can see the processes in its own PID namespace
+/// and in all the descendant namespaces, but not those in the ancestor namespaces.
+///
+/// Every thread is identified by its global ID in the kernel, which is its ID in the initial PID
+/// namespace. A PID namespace translates between the global IDs and the IDs in the namespace.
+///
+/// The first process in a PID namespace has a PID of 1. It becomes the init process of the
+/// namespace, which adopts the orphaned processes in the namespace. Once it exits, all the other
+/// processes in the namespace are killed and no new processes can be created in the namespace.
+//
+// TODO: Mount procfs per PID namespace, so that `/proc` only shows the visible processes, and
+// protect the init process from the signals that it does not handle.
+pub struct PidNamespace {
+    parent: Option<Arc<PidNamespace>>,
+    level: usize,
+    inner: SpinLock<Inner>,
+}
+
+struct Inner {
+    next_id: Pid,
+    /// The global IDs indexed by the IDs in the namespace.
+    global_ids: BTreeMap<Pid, Pid>,
+    /// The IDs in the namespace indexed by the global IDs.
+    local_ids: BTreeMap<Pid, Pid>,
+    /// Whether the init process has exited.
+    is_dead: bool,
+}
+
+impl PidNamespace {
+    /// Gets the initial PID namespace.
+    pub fn get_init_singleton() -> &'static Arc<PidNamespace> {
+        static INIT: Once<Arc<PidNamespace>> = Once::new();
+
+        INIT.call_once(|| Arc::new(Self::new(None, 0)))
+    }
+
+    fn new(parent: Option<Arc<PidNamespace>>, level: usize) -> Self {
+        Self {
+            parent,
+            level,
+            inner: SpinLock::new(Inner {
+                next_id: INIT_PID,
+                global_ids: BTreeMap::new(),
+                local_ids: BTreeMap::new(),
+                is_dead: false,
+            }),
+        }
+    }
+
+    /// Creates a child PID namespace.
+    pub fn new_child(self: &Arc<Self>) -> Result<Arc<Self>> {
+        if self.level >= MAX_LEVEL {
+            return_errno_with_message!(Errno::ENOSPC, "the PID namespaces are nested too deeply");
+        }
+
+        Ok(Arc::new(Self::new(Some(self.clone()), self.level + 1)))
+    }
+
+    /// Returns the parent PID namespace, or `None` for the initial PID namespace.
+    pub fn parent(&self) -> Option<&Arc<PidNamespace>> {
+        self.parent.as_ref()
+    }
+
+    /// Returns whether the namespace is the same as or an ancestor of `other`.
+    pub fn is_ancestor_of(&self, other: &PidNamespace) -> bool {
+        core::iter::successors(Some(other), |pid_ns| pid_ns.parent.as_deref())
+            .any(|pid_ns| core::ptr::eq(pid_ns, self))
+    }
+
+    /// Translates a global ID to the ID in the namespace.
+    ///
+    /// This method returns `None` if the thread is not visible in the namespace.
+    pub fn local_id(&self, global_id: Pid) -> Option<Pid> {
+        if self.parent.is_none() {
+            return Some(global_id);
+        }
+
+        self.inner.lock().local_ids.get(&global_id).copied()
+    }
+
+    /// Translates an ID in the namespace to the global ID.
+    ///
+    /// This method returns `None` if no thread has the ID in the namespace.
+    pub fn global_id(&self, local_id: Pid) -> Option<Pid> {
+        if self.parent.is_none() {
+            return Some(local_id);
+        }
+
+        self.inner.lock().global_ids.get(&local_id).copied()
+    }
+
+    /// Returns the global PID of the init process of the namespace.
+    pub fn init_process_id(&self) -> Option<Pid> {
+        self.global_id(INIT_PID)
+    }
+
+    /// Allocates the IDs for a new thread in the namespace and all the ancestor namespaces.
+    ///
+    /// This method fails with `ENOMEM` if the init process of any of the namespaces has exited.
+    pub(in crate::process) fn attach(&self, global_id: Pid) -> Result<()> {
+        for (attached, pid_ns) in self.ancestors().enumerate() {
+            let mut inner = pid_ns.inner.lock();
+            if inner.is_dead {
+                drop(inner);
+                self.ancestors()
+                    .take(attached)
+                    .for_each(|pid_ns| pid_ns.detach_one(global_id));
+                return_errno_with_message!(
+                    Errno::ENOMEM,
+                    "the init process of the PID namespace has exited"
+                );
+            }
+
+            let local_id = inner.next_id;
+            inner.next_id += 1;
+            inner.global_ids.insert(local_id, global_id);
+            inner.local_ids.insert(global_id, local_id);
+        }
+
+        Ok(())
+    }
+
+    /// Frees the IDs of a thread in the namespace and all the ancestor namespaces.
+    pub(in crate::process) fn detach(&self, global_id: Pid) {
+        self.ancestors()
+            .for_each(|pid_ns| pid_ns.detach_one(global_id));
+    }
+
+    fn detach_one(&self, global_id: Pid) {
+        let mut inner = self.inner.lock();
+        if let Some(local_id) = inner.local_ids.remove(&global_id) {
+            inner.global_ids.remove(&local_id);
+        }
+    }
+
+    /// Prevents new processes from being created in the namespace.
+    ///
+    /// This should be called when the init process of the namespace exits.
+    pub(in crate::process) fn set_dead(&self) {
+        self.inner.lock().is_dead = true;
+    }
+
+    /// Returns the namespace and its ancestors, excluding the initial PID namespace where the
+    /// IDs are the global IDs.
+    fn ancestors(&self) -> impl Iterator<Item = &PidNamespace> {
+        core::iter::successors(Some(self), |pid_ns| pid_ns.parent.as_deref())
+            .take_while(|pid_ns| pid_ns.parent.is_some())
+    }
+}
+
+impl Debug for PidNamespace {
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        f.debug_struct("PidNamespace")
+            .field("level", &self.level)
+            .finish_non_exhaustive()
+    }
+}
@@ -0,0 +1,102 @@
+// SPDX-License-Identifier: MPL-2.0
+
+use spin::Once;
+
+use crate::prelude::*;
+
+/// A UTS namespace.
+///
+/// A UTS namespace isolates the host name and the NIS domain name, which are returned by
+/// `uname(2)` and can be changed by `sethostname(2)` and `setdomainname(2)`.
+pub struct UtsNamespace {
+    uts_name: SpinLock<UtsName>,
+}
+
+const UTS_FIELD_LEN: usize = 65;
+
+/// The system information returned by `uname(2)`.
+#[derive(Debug, Clone, Copy, Pod)]
+#[repr(C)]
+pub struct UtsName {
+    sysname: [u8; UTS_FIELD_LEN],
+    nodename: [u8; UTS_FIELD_LEN],
+    release: [u8; UTS_FIELD_LEN],
+    version: [u8; UTS_FIELD_LEN],
+    machine: [u8; UTS_FIELD_LEN],
+    domainname: [u8; UTS_FIELD_LEN],
+}
+
+impl UtsNamespace {
+    /// The maximum length of the host name and the domain name, excluding the trailing zero.
+    pub const MAX_NAME_LEN: usize = UTS_FIELD_LEN - 1;
+
+    /// Gets the initial UTS namespace.
+    pub fn get_init_singleton() -> &'static Arc<UtsNamespace> {
+        static INIT: Once<Arc<UtsNamespace>> = Once::new();
+
+        INIT.call_once(|| {
+            // We don't use the real name and version of our os here. Instead, we pick up fake
+            // values witch is the same as the ones of linux. The values are used to fool glibc
+            // since glibc will check the version and os name.
+            let mut uts_name = UtsName::new_zeroed();
+            copy_name(b"Linux", &mut uts_name.sysname);
+            copy_name(b"WHITLEY", &mut uts_name.nodename);
+            copy_name(b"5.13.0", &mut uts_name.release);
+            copy_name(b"5.13.0", &mut uts_name.version);
+            copy_name(b"x86_64", &mut uts_name.machine);
+            copy_name(b"", &mut uts_name.domainname);
+
+            Arc::new(Self {
+                uts_name: SpinLock::new(uts_name),
+            })
+        })
+    }
+
+    /// Creates a new UTS namespace with the same names as this namespace.
+    pub fn copy(&self) -> Arc<Self> {
+        Arc::new(Self {
+            uts_name: SpinLock::new(self.uts_name()),
+        })
+    }
+
+    /// Returns the system information.
+    pub fn uts_name(&self) -> UtsName {
+        *self.uts_name.lock()
+    }
+
+    /// Sets the host name.
+    pub fn set_hostname(&self, name: &[u8]) -> Result<()> {
+        check_name_len(name)?;
+        copy_name(name, &mut self.uts_name.lock().nodename);
+        Ok(())
+    }
+
+    /// Sets the NIS domain name.
+    pub fn set_domainname(&self, name: &[u8]) -> Result<()> {
+        check_name_len(name)?;
+        copy_name(name, &mut self.uts_name.lock().domainname);
+        Ok(())
+    }
+}
+
+impl UtsName {
+    /// Returns the host name, excluding the trailing zeros.
+    pub fn nodename(&self) -> &[u8] {
+        let len = self.nodename.iter().position(|&b| b == 0).unwrap();
+        &self.nodename[..len]
+    }
+}
+
+fn check_name_len(name: &[u8]) -> Result<()> {
+    if name.len() > UtsNamespace::MAX_NAME_LEN {
+        return_errno_with_message!(Errno::EINVAL, "the name is too long");
+    }
+    Ok(())
+}
+
+/// Copies the name to the field, filling the rest of the field with zeros.
+fn copy_name(src: &[u8], dst: &mut [u8; UTS_FIELD_LEN]) {
+    let len = src.len().min(UtsNamespace::MAX_NAME_LEN);
+    dst[..len].copy_from_slice(&src[..len]);
+    dst[len..].fill(0);
+}
@@ -0,0 +1,97 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! The out-of-memory (OOM) killer.
+//!
+//! When the memory is exhausted, the OOM killer selects the process with the highest badness
+//! score and kills it to free its memory. The badness score is mainly determined by the
+//! resident set size (RSS) of the process, which can be adjusted by `/proc/[pid]/oom_score_adj`.
+//!
+//! Reference: <https://elixir.bootlin.com/linux/v6.0.9/source/mm/oom_kill.c>
+
+use ostd::mm::stat::mem_total;
+
+use super::{process_table, signal::signals::kernel::KernelSignal, Process};
+use crate::{prelude::*, process::signal::constants::SIGKILL, thread::Thread};
+
+/// The minimum value of `oom_score_adj`, which disables the OOM killer for the process.
+pub const OOM_SCORE_ADJ_MIN: i16 = -1000;
+/// The maximum value of `oom_score_adj`.
+pub const OOM_SCORE_ADJ_MAX: i16 = 1000;
+
+/// The last victim of the OOM killer.
+static LAST_VICTIM: Mutex<Weak<Process>> = Mutex::new(Weak::new());
+
+/// Kills a process to free memory because the memory is exhausted.
+///
+/// If the last victim is still exiting, no more processes will be killed. This method returns
+/// whether some memory is expected to be freed, i.e., either the last victim is still exiting
+/// or a new victim is killed.
+pub fn out_of_memory() -> bool {
+    let mut last_victim = LAST_VICTIM.lock();
+    if last_victim
+        .upgrade()
+        .is_some_and(|victim| !victim.status().is_zombie())
+    {
+        // Give the victim a chance to exit.
+        drop(last_victim);
+        Thread::yield_now();
+        return true;
+    }
+
+    let processes: Vec<_> = process_table::process_table_mut().iter().cloned().collect();
+    let Some((victim, points)) = processes
+        .into_iter()
+        .filter_map(|process| badness(&process).map(|points| (process, points)))
+        .max_by_key(|(_, points)| *points)
+    else {
+        error!("Out of memory: no killable processes");
+        return false;
+    };
+
+    warn!(
+        "Out of memory: killed process {} ({}), score {}, RSS {} kB, oom_score_adj {}",
+        victim.pid(),
+        victim.executable_path(),
+        points,
+        victim.root_vmar().resident_size() / 1024,
+        victim.oom_score_adj()
+    );
+    victim.enqueue_signal(KernelSignal::new(SIGKILL));
+    *last_victim = Arc::downgrade(&victim);
+
+    true
+}
+
+/// Returns the score shown in `/proc/[pid]/oom_score`.
+///
+/// The score is normalized to the range of 0 to 1000, where 1000 means that the process uses
+/// all the memory.
+pub fn oom_score(process: &Process) -> usize {
+    let Some(points) = badness(process) else {
+        return 0;
+    };
+
+    let total_pages = (mem_total() / PAGE_SIZE) as isize;
+    (points.max(0) * 1000 / total_pages.max(1)) as usize
+}
+
+/// Calculates the badness score of the process in pages.
+///
+/// If the process cannot be killed by the OOM killer, this method returns `None`.
+fn badness(process: &Process) -> Option<isize> {
+    let oom_score_adj = process.oom_score_adj();
+    if process.is_init_process()
+        || process.status().is_zombie()
+        || oom_score_adj == OOM_SCORE_ADJ_MIN
+    {
+        return None;
+    }
+
+    let rss_pages = (process.root_vmar().resident_size() / PAGE_SIZE) as isize;
+    let total_pages = (mem_total() / PAGE_SIZE) as isize;
+
+    // An adjustment of 1 means 0.1% of the total memory.
+    let points = rss_pages + oom_score_adj as isize * total_pages / 1000;
+    // A killable process should never have a score of zero.
+    Some(points.max(1))
+}
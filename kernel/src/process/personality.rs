@@ -0,0 +1,39 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! The execution domain of a process, which is set with the `personality` system call.
+//!
+//! Only the Linux execution domain is supported, but the flags are stored so that they are
+//! inherited across `fork` and `execve`. Among them, [`Personality::ADDR_NO_RANDOMIZE`] disables
+//! the user address space layout randomization.
+//!
+//! Reference: <https://man7.org/linux/man-pages/man2/personality.2.html>
+
+use crate::prelude::*;
+
+bitflags! {
+    /// The personality of a process.
+    ///
+    /// The lowest byte is the execution domain, which is zero (`PER_LINUX`) for Linux.
+    pub struct Personality: u32 {
+        const UNAME26            = 0x0020000;
+        const ADDR_NO_RANDOMIZE  = 0x0040000;
+        const FDPIC_FUNCPTRS     = 0x0080000;
+        const MMAP_PAGE_ZERO     = 0x0100000;
+        const ADDR_COMPAT_LAYOUT = 0x0200000;
+        const READ_IMPLIES_EXEC  = 0x0400000;
+        const ADDR_LIMIT_32BIT   = 0x0800000;
+        const SHORT_INODE        = 0x1000000;
+        const WHOLE_SECONDS      = 0x2000000;
+        const STICKY_TIMEOUTS    = 0x4000000;
+        const ADDR_LIMIT_3GB     = 0x8000000;
+    }
+}
+
+impl Personality {
+    /// The flags that are cleared when executing a set-user-ID or set-group-ID program, since
+    /// they make the program easier to exploit.
+    pub const CLEAR_ON_SETID: Self = Self::READ_IMPLIES_EXEC
+        .union(Self::ADDR_NO_RANDOMIZE)
+        .union(Self::ADDR_COMPAT_LAYOUT)
+        .union(Self::MMAP_PAGE_ZERO);
+}
@@ -0,0 +1,113 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! The file that refers to a process (i.e., a pidfd).
+//!
+//! A pidfd is created by `pidfd_open()` or by `clone()` with `CLONE_PIDFD`. Unlike a PID, it
+//! always refers to the same process even if the PID is reused after the process is reaped.
+//! It becomes readable when the process exits, so it can be monitored by `poll()` or `epoll()`.
+//! It is also hung up when the process is reaped.
+
+use core::sync::atomic::{AtomicBool, Ordering};
+
+use super::{
+    process_table,
+    signal::{PollHandle, Pollable},
+    Gid, Process, Uid,
+};
+use crate::{
+    events::IoEvents,
+    fs::{
+        file_handle::FileLike,
+        utils::{InodeMode, InodeType, Metadata, StatusFlags},
+    },
+    prelude::*,
+    time::clocks::RealTimeClock,
+};
+
+pub struct PidFile {
+    process: Arc<Process>,
+    is_nonblocking: AtomicBool,
+}
+
+impl PidFile {
+    pub fn new(process: Arc<Process>, is_nonblocking: bool) -> Self {
+        Self {
+            process,
+            is_nonblocking: AtomicBool::new(is_nonblocking),
+        }
+    }
+
+    /// Returns the process that the file refers to.
+    pub fn process(&self) -> &Arc<Process> {
+        &self.process
+    }
+
+    /// Returns whether the process has been reaped.
+    pub fn is_reaped(&self) -> bool {
+        // The PID may be reused after the process is reaped, so the process itself is compared.
+        !process_table::get_process(self.process.pid())
+            .is_some_and(|process| Arc::ptr_eq(&process, &self.process))
+    }
+
+    pub fn is_nonblocking(&self) -> bool {
+        self.is_nonblocking.load(Ordering::Relaxed)
+    }
+
+    fn check_io_events(&self) -> IoEvents {
+        if self.is_reaped() {
+            IoEvents::IN | IoEvents::HUP
+        } else if self.process.status().is_zombie() {
+            IoEvents::IN
+        } else {
+            IoEvents::empty()
+        }
+    }
+}
+
+impl Pollable for PidFile {
+    fn poll(&self, mask: IoEvents, poller: Option<&mut PollHandle>) -> IoEvents {
+        self.process
+            .pidfd_pollee()
+            .poll_with(mask, poller, || self.check_io_events())
+    }
+}
+
+impl FileLike for PidFile {
+    fn status_flags(&self) -> StatusFlags {
+        if self.is_nonblocking() {
+            StatusFlags::O_NONBLOCK
+        } else {
+            StatusFlags::empty()
+        }
+    }
+
+    fn set_status_flags(&self, new_flags: StatusFlags) -> Result<()> {
+        self.is_nonblocking.store(
+            new_flags.contains(StatusFlags::O_NONBLOCK),
+            Ordering::Relaxed,
+        );
+        Ok(())
+    }
+
+    fn metadata(&self) -> Metadata {
+        // This is a dummy implementation.
+        // TODO: Add "anonymous inode fs" and link `PidFile` to it.
+        let now = RealTimeClock::get().read_time();
+        Metadata {
+            dev: 0,
+            ino: 0,
+            size: 0,
+            blk_size: 0,
+            blocks: 0,
+            atime: now,
+            mtime: now,
+            ctime: now,
+            type_: InodeType::NamedPipe,
+            mode: InodeMode::from_bits_truncate(0o600),
+            nlinks: 1,
+            uid: Uid::new_root(),
+            gid: Gid::new_root(),
+            rdev: 0,
+        }
+    }
+}
@@ -2,20 +2,30 @@
 
 #![allow(dead_code)]
 
-use ostd::{cpu::CpuSet, sync::RwArc, task::Task, user::UserSpace};
+use core::sync::atomic::{AtomicBool, AtomicU32};
+
+use ostd::{
+    cpu::CpuSet,
+    sync::{RwArc, Waker},
+    task::Task,
+    user::UserSpace,
+};
 
 use super::{thread_table, PosixThread, ThreadLocal};
 use crate::{
     fs::{file_table::FileTable, thread_info::ThreadFsInfo},
     prelude::*,
     process::{
-        posix_thread::name::ThreadName,
-        signal::{sig_mask::AtomicSigMask, sig_queues::SigQueues},
+        namespace::NsProxy,
+        posix_thread::{name::ThreadName, PtraceState, Seccomp},
+        signal::{sig_mask::AtomicSigMask, sig_queues::SigQueues, SigStack},
         Credentials, Process,
     },
-    sched::priority::Priority,
+    sched::{SchedGroup, SchedPolicy},
+    security::{keys::ThreadKeyrings, landlock},
     thread::{task, Thread, Tid},
     time::{clocks::ProfClock, TimerManager},
+    vm::mempolicy::MemoryPolicy,
 };
 
 /// The builder to build a posix thread
@@ -30,11 +40,21 @@ pub struct PosixThreadBuilder {
     thread_name: Option<ThreadName>,
     set_child_tid: Vaddr,
     clear_child_tid: Vaddr,
+    vfork_done: Option<Arc<Waker>>,
     file_table: Option<RwArc<FileTable>>,
     fs: Option<Arc<ThreadFsInfo>>,
+    ns_proxy: Option<Arc<NsProxy>>,
+    no_new_privs: bool,
+    seccomp: Seccomp,
+    landlock_domain: Option<Arc<landlock::Domain>>,
+    keyrings: ThreadKeyrings,
+    memory_policy: Arc<MemoryPolicy>,
     sig_mask: AtomicSigMask,
     sig_queues: SigQueues,
-    priority: Priority,
+    sig_stack: SigStack,
+    sched_policy: SchedPolicy,
+    sched_group: Option<Arc<SchedGroup>>,
+    cpu_affinity: CpuSet,
 }
 
 impl PosixThreadBuilder {
@@ -47,11 +67,21 @@ impl PosixThreadBuilder {
             thread_name: None,
             set_child_tid: 0,
             clear_child_tid: 0,
+            vfork_done: None,
             file_table: None,
             fs: None,
+            ns_proxy: None,
+            no_new_privs: false,
+            seccomp: Seccomp::default(),
+            landlock_domain: None,
+            keyrings: ThreadKeyrings::new(),
+            memory_policy: MemoryPolicy::new_default(),
             sig_mask: AtomicSigMask::new_empty(),
             sig_queues: SigQueues::new(),
-            priority: Priority::default(),
+            sig_stack: SigStack::default(),
+            sched_policy: SchedPolicy::default(),
+            sched_group: None,
+            cpu_affinity: CpuSet::new_full(),
         }
     }
 
@@ -75,6 +105,12 @@ impl PosixThreadBuilder {
         self
     }
 
+    /// Sets the waker that is woken up when the thread calls `execve` or exits.
+    pub fn vfork_done(mut self, vfork_done: Option<Arc<Waker>>) -> Self {
+        self.vfork_done = vfork_done;
+        self
+    }
+
     pub fn file_table(mut self, file_table: RwArc<FileTable>) -> Self {
         self.file_table = Some(file_table);
         self
@@ -85,13 +121,58 @@ impl PosixThreadBuilder {
         self
     }
 
+    pub fn ns_proxy(mut self, ns_proxy: Arc<NsProxy>) -> Self {
+        self.ns_proxy = Some(ns_proxy);
+        self
+    }
+
+    pub fn no_new_privs(mut self, no_new_privs: bool) -> Self {
+        self.no_new_privs = no_new_privs;
+        self
+    }
+
+    pub fn seccomp(mut self, seccomp: Seccomp) -> Self {
+        self.seccomp = seccomp;
+        self
+    }
+
+    pub fn landlock_domain(mut self, landlock_domain: Option<Arc<landlock::Domain>>) -> Self {
+        self.landlock_domain = landlock_domain;
+        self
+    }
+
+    pub fn keyrings(mut self, keyrings: ThreadKeyrings) -> Self {
+        self.keyrings = keyrings;
+        self
+    }
+
+    pub fn memory_policy(mut self, memory_policy: Arc<MemoryPolicy>) -> Self {
+        self.memory_policy = memory_policy;
+        self
+    }
+
     pub fn sig_mask(mut self, sig_mask: AtomicSigMask) -> Self {
         self.sig_mask = sig_mask;
         self
     }
 
-    pub fn priority(mut self, priority: Priority) -> Self {
-        self.priority = priority;
+    pub fn sig_stack(mut self, sig_stack: SigStack) -> Self {
+        self.sig_stack = sig_stack;
+        self
+    }
+
+    pub fn sched_policy(mut self, sched_policy: SchedPolicy) -> Self {
+        self.sched_policy = sched_policy;
+        self
+    }
+
+    pub fn sched_group(mut self, sched_group: Arc<SchedGroup>) -> Self {
+        self.sched_group = Some(sched_group);
+        self
+    }
+
+    pub fn cpu_affinity(mut self, cpu_affinity: CpuSet) -> Self {
+        self.cpu_affinity = cpu_affinity;
         self
     }
 
@@ -104,17 +185,34 @@ impl PosixThreadBuilder {
             thread_name,
             set_child_tid,
             clear_child_tid,
+            vfork_done,
             file_table,
             fs,
+            ns_proxy,
+            no_new_privs,
+            seccomp,
+            landlock_domain,
+            keyrings,
+            memory_policy,
             sig_mask,
             sig_queues,
-            priority,
+            sig_stack,
+            sched_policy,
+            sched_group,
+            cpu_affinity,
         } = self;
 
         let file_table = file_table.unwrap_or_else(|| RwArc::new(FileTable::new_with_stdio()));
 
         let fs = fs.unwrap_or_else(|| Arc::new(ThreadFsInfo::default()));
 
+        let ns_proxy = ns_proxy.unwrap_or_else(|| NsProxy::get_init_singleton().clone());
+
+        let process_vm = process
+            .upgrade()
+            .expect("the process must be set before building the thread")
+            .vm();
+
         Arc::new_cyclic(|weak_task| {
             let posix_thread = {
                 let prof_clock = ProfClock::new();
@@ -123,11 +221,17 @@ impl PosixThreadBuilder {
 
                 PosixThread {
                     process,
-                    tid,
+                    tid: AtomicU32::new(tid),
                     name: Mutex::new(thread_name),
                     credentials,
-                    file_table: file_table.clone_ro(),
-                    fs,
+                    no_new_privs: AtomicBool::new(no_new_privs),
+                    seccomp: SpinLock::new(seccomp),
+                    landlock_domain: SpinLock::new(landlock_domain),
+                    keyrings: Mutex::new(keyrings),
+                    ptrace: PtraceState::new(),
+                    memory_policy: Mutex::new(memory_policy),
+                    file_table: Mutex::new(file_table.clone_ro()),
+                    ns_proxy: Mutex::new(ns_proxy),
                     sig_mask,
                     sig_queues,
                     signalled_waker: SpinLock::new(None),
@@ -137,15 +241,25 @@ impl PosixThreadBuilder {
                 }
             };
 
-            let cpu_affinity = CpuSet::new_full();
             let thread = Arc::new(Thread::new(
                 weak_task.clone(),
                 posix_thread,
-                priority,
+                sched_policy,
                 cpu_affinity,
             ));
+            if let Some(sched_group) = sched_group {
+                thread.sched_attr().set_group(sched_group);
+            }
 
-            let thread_local = ThreadLocal::new(set_child_tid, clear_child_tid, file_table);
+            let thread_local = ThreadLocal::new(
+                set_child_tid,
+                clear_child_tid,
+                vfork_done,
+                process_vm,
+                file_table,
+                fs,
+                sig_stack,
+            );
 
             thread_table::add_thread(tid, thread.clone());
             task::create_new_user_task(user_space, thread, thread_local)
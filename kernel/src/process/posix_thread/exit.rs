@@ -1,10 +1,10 @@
 // SPDX-License-Identifier: MPL-2.0
 
-use ostd::task::{CurrentTask, Task};
+use ostd::task::Task;
 
 use super::{
-    futex::futex_wake, robust_list::wake_robust_futex, thread_table, AsPosixThread, AsThreadLocal,
-    ThreadLocal,
+    futex::{futex_wake, handle_futex_death},
+    thread_table, AsPosixThread, AsThreadLocal, RobustListHead, ThreadLocal,
 };
 use crate::{
     current_userspace,
@@ -13,9 +13,9 @@ use crate::{
         exit::exit_process,
         signal::{constants::SIGKILL, signals::kernel::KernelSignal},
         task_set::TaskSet,
-        TermStatus,
+        Process, TermStatus,
     },
-    thread::{AsThread, Tid},
+    thread::{AsThread, Thread, Tid},
 };
 
 /// Exits the current POSIX thread.
@@ -36,6 +36,71 @@ pub fn do_exit_group(term_status: TermStatus) {
     exit_internal(term_status, true);
 }
 
+/// Kills all other threads in the current process and waits for them to exit.
+///
+/// This corresponds to Linux's `de_thread`, which is called by `execve` before the new program
+/// replaces the old one. The killed threads exit as if an `exit_group` is initiated, so they do
+/// not change the exit code of the process. If the current thread is not the main thread, it
+/// takes over the TID and the task slot of the main thread.
+///
+/// This method fails with [`Errno::EAGAIN`] if an `exit_group` has been initiated by another
+/// thread, in which case the current thread is about to be killed as well.
+pub fn kill_other_threads(ctx: &Context) -> Result<()> {
+    let other_threads: Vec<Arc<Thread>> = {
+        let mut tasks = ctx.process.tasks().lock();
+        if tasks.has_exited_group() {
+            return_errno_with_message!(Errno::EAGAIN, "the process is exiting");
+        }
+        if tasks.as_slice().len() == 1 {
+            return Ok(());
+        }
+
+        sigkill_other_threads(ctx.task, &tasks);
+        tasks.set_exited_group();
+        tasks
+            .as_slice()
+            .iter()
+            .filter(|task| !core::ptr::eq(ctx.task, task.as_ref()))
+            .map(|task| task.as_thread().unwrap().clone())
+            .collect()
+    };
+
+    for thread in other_threads {
+        thread.join();
+    }
+
+    let old_tid = ctx.posix_thread.tid();
+    {
+        let mut tasks = ctx.process.tasks().lock();
+        // Like Linux's `de_thread`, the new program runs in the main thread, so the current
+        // thread takes over the task slot and the TID of the exited main thread.
+        if !core::ptr::eq(ctx.task, tasks.main().as_ref()) {
+            tasks.replace_main(ctx.task);
+            ctx.posix_thread.set_tid(ctx.process.pid());
+        }
+        tasks.clear_exited_group();
+    }
+
+    if ctx.posix_thread.tid() != old_tid {
+        change_tid_references(ctx, old_tid);
+    }
+
+    Ok(())
+}
+
+/// Makes the references to the old TID of the current thread refer to its new TID.
+fn change_tid_references(ctx: &Context, old_tid: Tid) {
+    let new_tid = ctx.posix_thread.tid();
+
+    // The main thread stays in the thread table until the process is reaped, so its entry is
+    // replaced by the current thread.
+    thread_table::add_thread(new_tid, ctx.task.as_thread().unwrap().clone());
+    thread_table::remove_thread(old_tid);
+    ctx.process.pid_ns().detach(old_tid);
+
+    ctx.posix_thread.ptrace().change_tid(old_tid, new_tid);
+}
+
 /// Exits the current POSIX thread or process.
 fn exit_internal(term_status: TermStatus, is_exiting_group: bool) {
     let current_task = Task::current().unwrap();
@@ -71,12 +136,17 @@ fn exit_internal(term_status: TermStatus, is_exiting_group: bool) {
 
     wake_clear_ctid(thread_local);
 
-    wake_robust_list(thread_local, posix_thread.tid());
+    thread_local.wake_vfork_parent();
+
+    wake_robust_list(thread_local, posix_thread.ns_tid(), &posix_process);
+
+    posix_thread.ptrace().detach_on_exit(posix_thread.tid());
 
     // According to Linux behavior, the main thread shouldn't be removed from the table until the
     // process is reaped by its parent.
     if posix_thread.tid() != posix_process.pid() {
         thread_table::remove_thread(posix_thread.tid());
+        posix_process.pid_ns().detach(posix_thread.tid());
     }
 
     if is_last_thread {
@@ -86,10 +156,11 @@ fn exit_internal(term_status: TermStatus, is_exiting_group: bool) {
 
 /// Sends `SIGKILL` to all other threads in the current process.
 ///
-/// This is only needed when initiating an `exit_group` for the first time.
-fn sigkill_other_threads(current_task: &CurrentTask, task_set: &TaskSet) {
+/// This is only needed when initiating an `exit_group` for the first time, or when killing the
+/// other threads for `execve`.
+fn sigkill_other_threads(current_task: &Task, task_set: &TaskSet) {
     for task in task_set.as_slice() {
-        if core::ptr::eq(current_task.as_ref(), task.as_ref()) {
+        if core::ptr::eq(current_task, task.as_ref()) {
             continue;
         }
         task.as_posix_thread()
@@ -118,19 +189,22 @@ fn wake_clear_ctid(thread_local: &ThreadLocal) {
 /// Walks the robust futex list, marking futex dead and waking waiters.
 ///
 /// This corresponds to Linux's `exit_robust_list`. Errors are silently ignored.
-fn wake_robust_list(thread_local: &ThreadLocal, tid: Tid) {
-    let mut robust_list = thread_local.robust_list().borrow_mut();
+fn wake_robust_list(thread_local: &ThreadLocal, tid: Tid, process: &Process) {
+    let head_addr = thread_local.robust_list().replace(0);
+    if head_addr == 0 {
+        return;
+    }
 
-    let list_head = match *robust_list {
-        Some(robust_list_head) => robust_list_head,
-        None => return,
+    let Ok(list_head) = current_userspace!()
+        .read_val::<RobustListHead>(head_addr)
+        .inspect_err(|err| debug!("exit: cannot read the robust list head: {:?}", err))
+    else {
+        return;
     };
 
     trace!("exit: wake up the rubust list: {:?}", list_head);
-    for futex_addr in list_head.futexes() {
-        let _ = wake_robust_futex(futex_addr, tid)
+    for (futex_addr, is_pi) in list_head.futexes(head_addr) {
+        let _ = handle_futex_death(futex_addr, is_pi, tid, process.pid(), process.pid_ns())
             .inspect_err(|err| debug!("exit: cannot wake up the robust futex: {:?}", err));
     }
-
-    *robust_list = None;
 }
@@ -9,7 +9,14 @@ use ostd::{
 };
 use spin::Once;
 
-use crate::{prelude::*, process::Pid, time::wait::ManagedTimeout};
+use super::thread_table;
+use crate::{
+    current_userspace,
+    prelude::*,
+    process::{namespace::PidNamespace, Pid},
+    thread::Tid,
+    time::wait::ManagedTimeout,
+};
 
 type FutexBitSet = u32;
 type FutexBucketRef = Arc<Mutex<FutexBucket>>;
@@ -18,6 +25,16 @@ const FUTEX_OP_MASK: u32 = 0x0000_000F;
 const FUTEX_FLAGS_MASK: u32 = 0xFFFF_FFF0;
 const FUTEX_BITSET_MATCH_ANY: FutexBitSet = 0xFFFF_FFFF;
 
+/// The bit of the futex value indicating that there are waiters in the kernel.
+///
+/// This and the following constants are used by PI futexes and robust futexes, whose values
+/// contain the TID of the owner.
+pub(super) const FUTEX_WAITERS: u32 = 0x8000_0000;
+/// The bit of the futex value indicating that the owner exited without releasing the futex.
+pub(super) const FUTEX_OWNER_DIED: u32 = 0x4000_0000;
+/// The mask of the owner TID in the futex value.
+pub(super) const FUTEX_TID_MASK: u32 = 0x3FFF_FFFF;
+
 /// do futex wait
 pub fn futex_wait(
     futex_addr: u64,
@@ -116,29 +133,46 @@ pub fn futex_wake_bitset(
 }
 
 /// Does futex requeue
+///
+/// If `cmp_val` is specified, the operation fails with `EAGAIN` unless the futex value equals it.
+///
+/// Returns the total number of the woken waiters and the requeued waiters.
 pub fn futex_requeue(
     futex_addr: Vaddr,
     max_nwakes: usize,
     max_nrequeues: usize,
     futex_new_addr: Vaddr,
+    cmp_val: Option<i32>,
+    ctx: &Context,
     pid: Option<Pid>,
 ) -> Result<usize> {
-    if futex_new_addr == futex_addr {
-        return futex_wake(futex_addr, max_nwakes, pid);
-    }
-
     let futex_key = FutexKey::new(futex_addr, FUTEX_BITSET_MATCH_ANY, pid);
     let futex_new_key = FutexKey::new(futex_new_addr, FUTEX_BITSET_MATCH_ANY, pid);
     let (bucket_idx, futex_bucket_ref) = get_futex_bucket(futex_key);
     let (new_bucket_idx, futex_new_bucket_ref) = get_futex_bucket(futex_new_key);
 
-    let nwakes = {
+    let check_val = || -> Result<()> {
+        let Some(cmp_val) = cmp_val else {
+            return Ok(());
+        };
+        if futex_key.load_val(ctx)? != cmp_val {
+            return_errno_with_message!(Errno::EAGAIN, "the futex value does not match");
+        }
+        Ok(())
+    };
+
+    let count = {
         if bucket_idx == new_bucket_idx {
             let mut futex_bucket = futex_bucket_ref.lock();
+            check_val()?;
             let nwakes = futex_bucket.remove_and_wake_items(futex_key, max_nwakes);
-            futex_bucket.update_item_keys(futex_key, futex_new_key, max_nrequeues);
+            let nrequeues = if futex_new_addr == futex_addr {
+                0
+            } else {
+                futex_bucket.update_item_keys(futex_key, futex_new_key, max_nrequeues)
+            };
             drop(futex_bucket);
-            nwakes
+            nwakes + nrequeues
         } else {
             let (mut futex_bucket, mut futex_new_bucket) = {
                 if bucket_idx < new_bucket_idx {
@@ -153,17 +187,186 @@ pub fn futex_requeue(
                 }
             };
 
+            check_val()?;
             let nwakes = futex_bucket.remove_and_wake_items(futex_key, max_nwakes);
-            futex_bucket.requeue_items_to_another_bucket(
+            let nrequeues = futex_bucket.requeue_items_to_another_bucket(
                 futex_key,
                 &mut futex_new_bucket,
                 futex_new_key,
                 max_nrequeues,
             );
-            nwakes
+            nwakes + nrequeues
         }
     };
-    Ok(nwakes)
+    Ok(count)
+}
+
+/// Does futex lock PI
+///
+/// The futex value is set to the TID of the current thread if the futex is not owned by others.
+/// Otherwise, the current thread waits until the owner hands the futex to it in
+/// [`futex_unlock_pi`]. If `is_try` is true, the operation fails with `EAGAIN` instead of
+/// waiting.
+///
+/// While the current thread is waiting, the owner inherits its priority if it is higher than the
+/// priority of the owner.
+pub fn futex_lock_pi(
+    futex_addr: Vaddr,
+    timeout: Option<ManagedTimeout>,
+    is_try: bool,
+    ctx: &Context,
+    pid: Option<Pid>,
+) -> Result<()> {
+    debug!("futex_lock_pi addr: {:#x}, is_try: {}", futex_addr, is_try);
+
+    // The futex value contains the TID in the PID namespace, while the futex items contain the
+    // global TID.
+    let pid_ns = ctx.process.pid_ns();
+    let tid = ctx.posix_thread.tid();
+    let ns_tid = ctx.posix_thread.ns_tid();
+    let futex_key = FutexKey::new(futex_addr, FUTEX_BITSET_MATCH_ANY, pid);
+    let (_, futex_bucket_ref) = get_futex_bucket(futex_key);
+    let mut futex_bucket = futex_bucket_ref.lock();
+
+    // FIXME: The user space may update the futex value concurrently. We need atomic operations
+    // here.
+    let user_space = ctx.user_space();
+    let futex_val: u32 = user_space.read_val(futex_addr)?;
+
+    let owner = futex_val & FUTEX_TID_MASK;
+    if owner == 0 {
+        // Keep the `FUTEX_OWNER_DIED` bit so that the user space can know that the previous
+        // owner died.
+        let mut new_val = ns_tid | (futex_val & FUTEX_OWNER_DIED);
+        if futex_bucket.has_pi_item(futex_key) {
+            new_val |= FUTEX_WAITERS;
+        }
+        user_space.write_val(futex_addr, &new_val)?;
+        return Ok(());
+    }
+    if owner == ns_tid {
+        return_errno_with_message!(
+            Errno::EDEADLK,
+            "the PI futex is already owned by the thread"
+        );
+    }
+    if is_try {
+        return_errno_with_message!(Errno::EAGAIN, "the PI futex is owned by another thread");
+    }
+    let Some(owner) = pid_ns
+        .global_id(owner)
+        .filter(|owner| thread_table::get_thread(*owner).is_some())
+    else {
+        return_errno_with_message!(Errno::ESRCH, "the owner of the PI futex does not exist");
+    };
+
+    if futex_val & FUTEX_WAITERS == 0 {
+        user_space.write_val(futex_addr, &(futex_val | FUTEX_WAITERS))?;
+    }
+
+    let (futex_item, waiter) = FutexItem::create_pi(futex_key, tid);
+    let waker = futex_item.waker.clone();
+    futex_bucket.add_item(futex_item);
+    futex_bucket.update_pi_boost(futex_key, owner);
+    drop(futex_bucket);
+
+    let res = waiter.pause_timeout(timeout);
+
+    // If the item has been removed from the bucket, the futex has been handed to us, regardless
+    // of whether the waiting is interrupted or timed out.
+    let mut futex_bucket = futex_bucket_ref.lock();
+    if futex_bucket.remove_item_with_waker(&waker) {
+        // The owner no longer inherits the priority of the current thread.
+        if let Ok(futex_val) = user_space.read_val::<u32>(futex_addr) {
+            if let Some(owner) = pid_ns.global_id(futex_val & FUTEX_TID_MASK) {
+                futex_bucket.update_pi_boost(futex_key, owner);
+            }
+        }
+        res?;
+        return_errno_with_message!(Errno::EAGAIN, "the PI futex waiter is woken spuriously");
+    }
+
+    Ok(())
+}
+
+/// Does futex unlock PI
+///
+/// The futex is handed to the first waiter, if any.
+pub fn futex_unlock_pi(futex_addr: Vaddr, ctx: &Context, pid: Option<Pid>) -> Result<()> {
+    debug!("futex_unlock_pi addr: {:#x}", futex_addr);
+
+    let futex_key = FutexKey::new(futex_addr, FUTEX_BITSET_MATCH_ANY, pid);
+    let (_, futex_bucket_ref) = get_futex_bucket(futex_key);
+    let mut futex_bucket = futex_bucket_ref.lock();
+
+    let user_space = ctx.user_space();
+    let futex_val: u32 = user_space.read_val(futex_addr)?;
+    if futex_val & FUTEX_TID_MASK != ctx.posix_thread.ns_tid() {
+        return_errno_with_message!(Errno::EPERM, "the PI futex is not owned by the thread");
+    }
+
+    futex_bucket.hand_off_pi(futex_key, 0, &user_space, ctx.process.pid_ns())?;
+
+    // The current thread no longer inherits the priorities of the waiters.
+    ctx.thread.sched_attr().set_pi_boost(None);
+
+    Ok(())
+}
+
+/// Marks the futex owned by the exited thread as dead, and wakes up a waiter.
+///
+/// For PI futexes, the futex is handed to the first waiter, if any.
+///
+/// The TID is the one in the PID namespace of the process, which is the one stored in the futex.
+///
+/// This corresponds to Linux's `handle_futex_death`.
+pub fn handle_futex_death(
+    futex_addr: Vaddr,
+    is_pi: bool,
+    tid: Tid,
+    pid: Pid,
+    pid_ns: &PidNamespace,
+) -> Result<()> {
+    if futex_addr == 0 {
+        return_errno_with_message!(Errno::EINVAL, "invalid futex addr");
+    }
+
+    // The futex can be process private or shared. We don't know which one it is, so we try the
+    // private one first.
+    let private_key = FutexKey::new(futex_addr, FUTEX_BITSET_MATCH_ANY, Some(pid));
+    let shared_key = FutexKey::new(futex_addr, FUTEX_BITSET_MATCH_ANY, None);
+    let (_, futex_bucket_ref) = get_futex_bucket(private_key);
+    let mut futex_bucket = futex_bucket_ref.lock();
+
+    // FIXME: The user space may update the futex value concurrently. We need atomic operations
+    // here.
+    let user_space = current_userspace!();
+    let futex_val: u32 = user_space.read_val(futex_addr)?;
+    if futex_val & FUTEX_TID_MASK != tid {
+        // The futex is not owned by the thread. Do nothing.
+        return Ok(());
+    }
+
+    if is_pi {
+        let futex_key = if futex_bucket.has_pi_item(private_key) {
+            private_key
+        } else {
+            shared_key
+        };
+        return futex_bucket.hand_off_pi(futex_key, FUTEX_OWNER_DIED, &user_space, pid_ns);
+    }
+
+    let new_val = (futex_val & FUTEX_WAITERS) | FUTEX_OWNER_DIED;
+    user_space.write_val(futex_addr, &new_val)?;
+
+    if futex_val & FUTEX_WAITERS != 0 {
+        debug!("wake robust futex addr: {:#x}", futex_addr);
+        if futex_bucket.remove_and_wake_items(private_key, 1) == 0 {
+            futex_bucket.remove_and_wake_items(shared_key, 1);
+        }
+    }
+
+    Ok(())
 }
 
 static FUTEX_BUCKETS: Once<FutexBucketVec> = Once::new();
@@ -249,6 +452,19 @@ impl FutexBucket {
         }
     }
 
+    pub fn remove_item_with_waker(&mut self, waker: &Arc<Waker>) -> bool {
+        let mut item_cursor = self.items.front_mut();
+        while let Some(item) = item_cursor.get() {
+            if Arc::ptr_eq(&item.waker, waker) {
+                let _ = item_cursor.remove();
+                return true;
+            }
+            item_cursor.move_next();
+        }
+
+        false
+    }
+
     pub fn remove_and_wake_items(&mut self, key: FutexKey, max_count: usize) -> usize {
         let mut count = 0;
         let mut item_cursor = self.items.front_mut();
@@ -256,7 +472,7 @@ impl FutexBucket {
             // The item_cursor has been checked not null.
             let item = item_cursor.get().unwrap();
 
-            if !item.key.match_up(&key) {
+            if !item.key.match_up(&key) || item.is_pi() {
                 item_cursor.move_next();
                 continue;
             }
@@ -271,14 +487,19 @@ impl FutexBucket {
         count
     }
 
-    pub fn update_item_keys(&mut self, key: FutexKey, new_key: FutexKey, max_count: usize) {
+    pub fn update_item_keys(
+        &mut self,
+        key: FutexKey,
+        new_key: FutexKey,
+        max_count: usize,
+    ) -> usize {
         let mut count = 0;
         let mut item_cursor = self.items.front_mut();
         while !item_cursor.is_null() && count < max_count {
             // The item_cursor has been checked not null.
             let item = item_cursor.get().unwrap();
 
-            if !item.key.match_up(&key) {
+            if !item.key.match_up(&key) || item.is_pi() {
                 item_cursor.move_next();
                 continue;
             }
@@ -288,6 +509,8 @@ impl FutexBucket {
             item_cursor.insert_before(item);
             count += 1;
         }
+
+        count
     }
 
     pub fn requeue_items_to_another_bucket(
@@ -296,14 +519,14 @@ impl FutexBucket {
         another: &mut Self,
         new_key: FutexKey,
         max_nrequeues: usize,
-    ) {
+    ) -> usize {
         let mut count = 0;
         let mut item_cursor = self.items.front_mut();
         while !item_cursor.is_null() && count < max_nrequeues {
             // The item_cursor has been checked not null.
             let item = item_cursor.get().unwrap();
 
-            if !item.key.match_up(&key) {
+            if !item.key.match_up(&key) || item.is_pi() {
                 item_cursor.move_next();
                 continue;
             }
@@ -313,27 +536,120 @@ impl FutexBucket {
             another.add_item(item);
             count += 1;
         }
+
+        count
+    }
+
+    pub fn has_pi_item(&self, key: FutexKey) -> bool {
+        self.items
+            .iter()
+            .any(|item| item.key.match_up(&key) && item.is_pi())
+    }
+
+    /// Hands the PI futex to the first waiter.
+    ///
+    /// The futex value is set to the TID of the waiter, or zero if there are no waiters, with the
+    /// `extra_bits` set.
+    pub fn hand_off_pi(
+        &mut self,
+        key: FutexKey,
+        extra_bits: u32,
+        user_space: &CurrentUserSpace,
+        pid_ns: &PidNamespace,
+    ) -> Result<()> {
+        let mut item_cursor = self.items.front_mut();
+        while let Some(item) = item_cursor.get() {
+            if item.key.match_up(&key) && item.is_pi() {
+                break;
+            }
+            item_cursor.move_next();
+        }
+
+        let Some(new_owner) = item_cursor.get().map(|item| item.pi_tid.unwrap()) else {
+            user_space.write_val(key.addr(), &extra_bits)?;
+            return Ok(());
+        };
+
+        let item = item_cursor.remove().unwrap();
+        // FIXME: The waiter may be invisible in the PID namespace if the futex is shared with a
+        // process in another PID namespace. In this case, the futex value does not contain a
+        // valid owner.
+        let mut new_val = pid_ns.local_id(new_owner).unwrap_or(0) | extra_bits;
+        if self.has_pi_item(key) {
+            new_val |= FUTEX_WAITERS;
+        }
+        if let Err(err) = user_space.write_val(key.addr(), &new_val) {
+            // Put the item back since the futex is not handed to the waiter.
+            self.items.push_front(item);
+            return Err(err);
+        }
+
+        // The waiter will find that its item has been removed, so it knows that it owns the
+        // futex even if the wakeup fails.
+        let _ = item.wake();
+
+        // The new owner inherits the priorities of the remaining waiters.
+        self.update_pi_boost(key, new_owner);
+
+        Ok(())
+    }
+
+    /// Lets the owner of the PI futex inherit the highest priority of the waiters.
+    ///
+    /// The owner is scheduled with its own policy again if no waiters have real-time priorities.
+    //
+    // TODO: Track all the PI futexes owned by a thread. For now, only the waiters of the given
+    // futex are considered, and the inherited priority is not propagated along a chain of owners.
+    pub fn update_pi_boost(&self, key: FutexKey, owner: Tid) {
+        let Some(owner_thread) = thread_table::get_thread(owner) else {
+            return;
+        };
+
+        let pi_boost = self
+            .items
+            .iter()
+            .filter(|item| item.key.match_up(&key))
+            .filter_map(|item| item.pi_tid)
+            .filter_map(thread_table::get_thread)
+            .filter_map(|thread| thread.sched_attr().effective_rt_prio())
+            .min();
+        owner_thread.sched_attr().set_pi_boost(pi_boost);
     }
 }
 
 struct FutexItem {
     key: FutexKey,
     waker: Arc<Waker>,
+    /// The TID of the waiter if it waits for a PI futex.
+    pi_tid: Option<Tid>,
     link: LinkedListAtomicLink,
 }
 
 impl FutexItem {
     pub fn create(key: FutexKey) -> (Box<Self>, Waiter) {
+        Self::create_inner(key, None)
+    }
+
+    pub fn create_pi(key: FutexKey, tid: Tid) -> (Box<Self>, Waiter) {
+        Self::create_inner(key, Some(tid))
+    }
+
+    fn create_inner(key: FutexKey, pi_tid: Option<Tid>) -> (Box<Self>, Waiter) {
         let (waiter, waker) = Waiter::new_pair();
         let futex_item = Box::new(FutexItem {
             key,
             waker,
+            pi_tid,
             link: LinkedListAtomicLink::new(),
         });
 
         (futex_item, waiter)
     }
 
+    pub fn is_pi(&self) -> bool {
+        self.pi_tid.is_some()
+    }
+
     #[must_use]
     pub fn wake(&self) -> bool {
         self.waker.wake_up()
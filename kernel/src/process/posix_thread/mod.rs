@@ -2,13 +2,14 @@
 
 #![allow(dead_code)]
 
-use core::sync::atomic::{AtomicU32, Ordering};
+use core::sync::atomic::{AtomicBool, AtomicU32, Ordering};
 
 use aster_rights::{ReadOp, WriteOp};
 use ostd::sync::{RoArc, Waker};
 
 use super::{
     kill::SignalSenderIds,
+    namespace::NsProxy,
     signal::{
         sig_action::SigAction,
         sig_mask::{AtomicSigMask, SigMask, SigSet},
@@ -21,11 +22,13 @@ use super::{
 };
 use crate::{
     events::Observer,
-    fs::{file_table::FileTable, thread_info::ThreadFsInfo},
+    fs::file_table::FileTable,
     prelude::*,
     process::signal::constants::SIGCONT,
+    security::{keys::ThreadKeyrings, landlock},
     thread::{Thread, Tid},
     time::{clocks::ProfClock, Timer, TimerManager},
+    vm::mempolicy::MemoryPolicy,
 };
 
 mod builder;
@@ -33,33 +36,55 @@ mod exit;
 pub mod futex;
 mod name;
 mod posix_thread_ext;
+pub mod ptrace;
 mod robust_list;
+pub mod seccomp;
 mod thread_local;
 pub mod thread_table;
 
 pub use builder::PosixThreadBuilder;
-pub use exit::{do_exit, do_exit_group};
+pub use exit::{do_exit, do_exit_group, kill_other_threads};
 pub use name::{ThreadName, MAX_THREAD_NAME_LEN};
 pub use posix_thread_ext::{create_posix_task_from_executable, AsPosixThread};
+pub use ptrace::PtraceState;
 pub use robust_list::RobustListHead;
+pub use seccomp::Seccomp;
 pub use thread_local::{AsThreadLocal, ThreadLocal};
 
 pub struct PosixThread {
     // Immutable part
     process: Weak<Process>,
-    tid: Tid,
 
     // Mutable part
+    /// The global thread ID.
+    ///
+    /// The ID only changes when a non-main thread calls `execve` and takes over the ID of the
+    /// main thread.
+    tid: AtomicU32,
     name: Mutex<Option<ThreadName>>,
 
     /// Process credentials. At the kernel level, credentials are a per-thread attribute.
     credentials: Credentials,
+    /// Whether `execve` is prevented from granting privileges (e.g., with set-user-ID programs).
+    no_new_privs: AtomicBool,
+    /// The system calls that the thread is allowed to make.
+    seccomp: SpinLock<Seccomp>,
+    /// The Landlock domain that restricts the accesses of the thread.
+    landlock_domain: SpinLock<Option<Arc<landlock::Domain>>>,
+    /// The keyrings that are searched for the keys requested by the thread.
+    keyrings: Mutex<ThreadKeyrings>,
+    /// The state of being traced by another process.
+    ptrace: PtraceState,
+    /// The NUMA memory policy of the thread.
+    memory_policy: Mutex<Arc<MemoryPolicy>>,
 
     // Files
     /// File table
-    file_table: RoArc<FileTable>,
-    /// File system
-    fs: Arc<ThreadFsInfo>,
+    file_table: Mutex<RoArc<FileTable>>,
+
+    // Namespaces
+    /// The namespaces that the thread belongs to.
+    ns_proxy: Mutex<Arc<NsProxy>>,
 
     // Signal
     /// Blocked signals
@@ -90,20 +115,85 @@ impl PosixThread {
     }
 
     /// Returns the thread id
+    /// Returns the global thread ID.
     pub fn tid(&self) -> Tid {
-        self.tid
+        self.tid.load(Ordering::Relaxed)
+    }
+
+    /// Sets the global thread ID.
+    ///
+    /// This should only be called when the thread takes over the main thread in `execve`.
+    pub(super) fn set_tid(&self, tid: Tid) {
+        self.tid.store(tid, Ordering::Relaxed);
+    }
+
+    /// Returns the thread ID in the PID namespace of the process.
+    pub fn ns_tid(&self) -> Tid {
+        self.process().pid_ns().local_id(self.tid()).unwrap()
     }
 
     pub fn thread_name(&self) -> &Mutex<Option<ThreadName>> {
         &self.name
     }
 
-    pub fn file_table(&self) -> &RoArc<FileTable> {
+    /// Returns the file table.
+    ///
+    /// Only the current thread may replace its file table.
+    pub fn file_table(&self) -> &Mutex<RoArc<FileTable>> {
         &self.file_table
     }
 
-    pub fn fs(&self) -> &Arc<ThreadFsInfo> {
-        &self.fs
+    /// Returns the namespaces that the thread belongs to.
+    ///
+    /// Only the current thread may replace its namespaces.
+    pub fn ns_proxy(&self) -> &Mutex<Arc<NsProxy>> {
+        &self.ns_proxy
+    }
+
+    /// Returns whether `execve` is prevented from granting privileges.
+    pub fn no_new_privs(&self) -> bool {
+        self.no_new_privs.load(Ordering::Relaxed)
+    }
+
+    /// Prevents `execve` from granting privileges.
+    ///
+    /// Once set, this cannot be unset.
+    pub fn set_no_new_privs(&self) {
+        self.no_new_privs.store(true, Ordering::Relaxed);
+    }
+
+    /// Returns the NUMA memory policy of the thread.
+    pub fn memory_policy(&self) -> &Mutex<Arc<MemoryPolicy>> {
+        &self.memory_policy
+    }
+
+    /// Returns the seccomp state of the thread.
+    ///
+    /// Only the current thread may change its seccomp state, except that a thread may
+    /// synchronize its filters to the other threads in the same process. The state should only be
+    /// changed with the lock of the process's tasks held.
+    pub fn seccomp(&self) -> &SpinLock<Seccomp> {
+        &self.seccomp
+    }
+
+    /// Returns the Landlock domain of the thread, or `None` if the thread is not restricted.
+    ///
+    /// Only the current thread may change its domain.
+    pub fn landlock_domain(&self) -> &SpinLock<Option<Arc<landlock::Domain>>> {
+        &self.landlock_domain
+    }
+
+    /// Returns the keyrings of the thread.
+    ///
+    /// Only the current thread may change its keyrings, except that the process keyring is
+    /// shared by the threads in the process.
+    pub fn keyrings(&self) -> &Mutex<ThreadKeyrings> {
+        &self.keyrings
+    }
+
+    /// Returns the ptrace state of the thread.
+    pub fn ptrace(&self) -> &PtraceState {
+        &self.ptrace
     }
 
     /// Get the reference to the signal mask of the thread.
@@ -116,14 +206,24 @@ impl PosixThread {
     }
 
     pub fn sig_pending(&self) -> SigSet {
-        self.sig_queues.sig_pending()
+        let pending = self.sig_queues.sig_pending();
+        match self.process.upgrade() {
+            Some(process) => pending | process.sig_queues().sig_pending(),
+            None => pending,
+        }
     }
 
     /// Returns whether the thread has some pending signals
     /// that are not blocked.
+    ///
+    /// Both the thread-directed signals and the process-directed signals are taken into account.
     pub fn has_pending(&self) -> bool {
         let blocked = self.sig_mask().load(Ordering::Relaxed);
         self.sig_queues.has_pending(blocked)
+            || self
+                .process
+                .upgrade()
+                .is_some_and(|process| process.sig_queues().has_pending(blocked))
     }
 
     /// Returns whether the signal is blocked by the thread.
@@ -144,7 +244,7 @@ impl PosixThread {
         signum: Option<&SigNum>,
         sender: &SignalSenderIds,
     ) -> Result<()> {
-        if sender.euid().is_root() {
+        if sender.can_kill_any() {
             return Ok(());
         }
 
@@ -201,12 +301,24 @@ impl PosixThread {
         *self.signalled_waker.lock() = None;
     }
 
+    /// Returns whether the signalled waker of this thread is set.
+    ///
+    /// The signalled waker is set when the thread waits interruptibly.
+    pub fn has_signalled_waker(&self) -> bool {
+        self.signalled_waker.lock().is_some()
+    }
+
     /// Enqueues a thread-directed signal. This method should only be used for enqueue kernel
     /// signal and fault signal.
     pub fn enqueue_signal(&self, signal: Box<dyn Signal>) {
         let signal_number = signal.num();
         self.sig_queues.enqueue(signal);
-        if self.process().sig_dispositions().lock().get(signal_number) != SigAction::Ign
+        self.wake_up_by_signal(signal_number);
+    }
+
+    /// Wakes up the thread if it is waiting interruptibly and the signal is not ignored.
+    pub(in crate::process) fn wake_up_by_signal(&self, signum: SigNum) {
+        if self.process().sig_dispositions().lock().get(signum) != SigAction::Ign
             && let Some(waker) = &*self.signalled_waker.lock()
         {
             waker.wake_up();
@@ -240,8 +352,15 @@ impl PosixThread {
         self.prof_timer_manager.process_expired_timers();
     }
 
+    /// Dequeues a pending signal that is not blocked by `mask`.
+    ///
+    /// The thread-directed signals are dequeued before the process-directed signals.
     pub fn dequeue_signal(&self, mask: &SigMask) -> Option<Box<dyn Signal>> {
-        self.sig_queues.dequeue(mask)
+        self.sig_queues.dequeue(mask).or_else(|| {
+            self.process
+                .upgrade()
+                .and_then(|process| process.sig_queues().dequeue(mask))
+        })
     }
 
     pub fn register_sigqueue_observer(
@@ -13,7 +13,11 @@ use crate::{
         thread_info::ThreadFsInfo,
     },
     prelude::*,
-    process::{process_vm::ProcessVm, program_loader::load_program_to_vm, Credentials, Process},
+    process::{
+        process_vm::ProcessVm,
+        program_loader::{load_program_to_vm, ExecIds},
+        Credentials, Process,
+    },
     thread::{AsThread, Thread, Tid},
 };
 
@@ -52,13 +56,21 @@ pub fn create_posix_task_from_executable(
         let fs_resolver = fs.resolver().read();
         let fs_path = FsPath::new(AT_FDCWD, executable_path)?;
         let elf_file = fs.resolver().read().lookup(&fs_path)?;
-        load_program_to_vm(process_vm, elf_file, argv, envp, &fs_resolver, 1)?
+        let exec_ids = ExecIds {
+            uid: credentials.ruid(),
+            euid: credentials.euid(),
+            gid: credentials.rgid(),
+            egid: credentials.egid(),
+        };
+        load_program_to_vm(process_vm, elf_file, argv, envp, &fs_resolver, exec_ids)?
     };
 
     let vm_space = process_vm.root_vmar().vm_space().clone();
     let mut cpu_ctx = UserContext::default();
     cpu_ctx.set_instruction_pointer(elf_load_info.entry_point() as _);
     cpu_ctx.set_stack_pointer(elf_load_info.user_stack_top() as _);
+    #[cfg(target_arch = "x86_64")]
+    cpu_ctx.set_compat_mode(elf_load_info.is_compat());
     let user_space = Arc::new(UserSpace::new(vm_space, cpu_ctx));
     let thread_name = Some(ThreadName::new_from_executable_path(executable_path)?);
     let thread_builder = PosixThreadBuilder::new(tid, user_space, credentials)
@@ -0,0 +1,503 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! Process tracing, which allows a tracer process to observe and control other threads.
+//!
+//! A traced thread (i.e., a tracee) enters a ptrace-stop before it handles a signal, after it
+//! executes a new program, and optionally when it enters or exits a system call. The tracer is
+//! notified of the ptrace-stops via `wait4(2)`, and can examine and change the registers and the
+//! memory of the tracee before resuming it.
+//!
+//! For more details, see <https://man7.org/linux/man-pages/man2/ptrace.2.html>.
+
+use ostd::{cpu::UserContext, sync::WaitQueue};
+
+use super::AsPosixThread;
+use crate::{
+    arch::ptrace::PtraceRegs,
+    prelude::*,
+    process::{
+        signal::{
+            c_types::siginfo_t,
+            constants::{SIGCHLD, SIGKILL, SIGTRAP},
+            sig_mask::SigSet,
+            sig_num::SigNum,
+            signals::{kernel::KernelSignal, Signal},
+            with_signal_blocked, Pause,
+        },
+        Process,
+    },
+    thread::{Thread, Tid},
+};
+
+bitflags! {
+    /// The options of a tracee, which are set with `PTRACE_SETOPTIONS`.
+    pub struct PtraceOptions: u32 {
+        /// Sets bit 7 of the signal number reported at syscall-stops.
+        const TRACESYSGOOD = 1 << 0;
+        /// Stops the tracee at the next `execve` with `PTRACE_EVENT_EXEC`.
+        const TRACEEXEC = 1 << 4;
+        /// Kills the tracee when the tracer exits.
+        const EXITKILL = 1 << 20;
+    }
+}
+
+/// How the tracer resumes a tracee.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PtraceResumeMode {
+    /// Resumes the tracee (i.e., `PTRACE_CONT`).
+    Continue,
+    /// Resumes the tracee and stops it at the next syscall entry or exit (i.e., `PTRACE_SYSCALL`).
+    Syscall,
+    /// Resumes the tracee and stops it after a single instruction (i.e., `PTRACE_SINGLESTEP`).
+    SingleStep,
+}
+
+const PTRACE_EVENT_EXEC: u32 = 4;
+
+/// The ptrace state of a thread.
+pub struct PtraceState {
+    inner: SpinLock<Inner>,
+    /// The wait queue where the thread waits to be resumed in ptrace-stops.
+    wait_queue: WaitQueue,
+}
+
+#[derive(Default)]
+struct Inner {
+    tracer: Option<Tracer>,
+    stop: Option<PtraceStop>,
+    /// Whether the thread is resumed with single-stepping enabled, which should be disabled at
+    /// the next ptrace-stop.
+    is_single_stepping: bool,
+}
+
+struct Tracer {
+    process: Weak<Process>,
+    options: PtraceOptions,
+    /// Whether the tracee stops at syscall entries and exits.
+    is_tracing_syscalls: bool,
+    /// The message about the last ptrace event, which is retrieved with `PTRACE_GETEVENTMSG`.
+    event_msg: usize,
+}
+
+/// A ptrace-stop of a tracee.
+struct PtraceStop {
+    /// The wait status that is reported to the tracer.
+    status: u32,
+    siginfo: siginfo_t,
+    /// The registers, which are written back when the tracee is resumed.
+    regs: PtraceRegs,
+    /// The signal to handle after the tracee is resumed.
+    ///
+    /// This is `None` if the stop is not a signal-delivery-stop, or if the tracer discards the
+    /// signal.
+    signal: Option<SigNum>,
+    is_reported: bool,
+    is_resumed: bool,
+}
+
+enum StopKind {
+    Signal(SigNum, siginfo_t),
+    Syscall,
+    Exec,
+}
+
+impl PtraceState {
+    pub(super) fn new() -> Self {
+        Self {
+            inner: SpinLock::new(Inner::default()),
+            wait_queue: WaitQueue::new(),
+        }
+    }
+
+    /// Returns whether the thread is traced.
+    pub fn is_traced(&self) -> bool {
+        self.inner.lock().tracer.is_some()
+    }
+
+    /// Returns whether the thread is traced by `tracer`.
+    pub fn is_traced_by(&self, tracer: &Process) -> bool {
+        self.inner.lock().is_traced_by(tracer)
+    }
+
+    // ******************* Tracer ********************
+
+    /// Makes `tracer` trace the thread.
+    ///
+    /// This method fails with `EPERM` if the thread is already traced.
+    pub fn attach(&self, tracee: &Arc<Thread>, tracer: &Arc<Process>) -> Result<()> {
+        let mut tracees = tracer.tracees().lock();
+        let mut inner = self.inner.lock();
+
+        if inner.tracer.is_some() {
+            return_errno_with_message!(Errno::EPERM, "the thread is already traced");
+        }
+
+        inner.tracer = Some(Tracer {
+            process: Arc::downgrade(tracer),
+            options: PtraceOptions::empty(),
+            is_tracing_syscalls: false,
+            event_msg: 0,
+        });
+        tracees.insert(tracee.as_posix_thread().unwrap().tid(), tracee.clone());
+
+        Ok(())
+    }
+
+    /// Stops tracing the thread and resumes it with `signal`.
+    ///
+    /// The methods that operate on behalf of the tracer (except [`Self::attach`]) fail with
+    /// `ESRCH` if the thread is not traced by `tracer` or is not in a ptrace-stop.
+    pub fn detach(&self, tid: Tid, tracer: &Process, signal: Option<SigNum>) -> Result<()> {
+        let mut tracees = tracer.tracees().lock();
+        let mut inner = self.inner.lock();
+
+        let (_, stop) = inner.stopped_by(tracer)?;
+        stop.resume(signal);
+        inner.tracer = None;
+        inner.is_single_stepping = false;
+        tracees.remove(&tid);
+
+        drop(inner);
+        self.wait_queue.wake_all();
+        Ok(())
+    }
+
+    /// Resumes the thread with `signal`.
+    pub fn resume(
+        &self,
+        tracer: &Process,
+        mode: PtraceResumeMode,
+        signal: Option<SigNum>,
+    ) -> Result<()> {
+        let mut inner = self.inner.lock();
+
+        let (tracer_state, stop) = inner.stopped_by(tracer)?;
+        if mode == PtraceResumeMode::SingleStep {
+            stop.regs.set_single_step(true)?;
+        }
+        tracer_state.is_tracing_syscalls = mode == PtraceResumeMode::Syscall;
+        stop.resume(signal);
+        inner.is_single_stepping = mode == PtraceResumeMode::SingleStep;
+
+        drop(inner);
+        self.wait_queue.wake_all();
+        Ok(())
+    }
+
+    /// Checks whether the thread is traced by `tracer` and is in a ptrace-stop.
+    pub fn check_stopped_by(&self, tracer: &Process) -> Result<()> {
+        self.inner.lock().stopped_by(tracer)?;
+        Ok(())
+    }
+
+    /// Sets the options.
+    pub fn set_options(&self, tracer: &Process, options: PtraceOptions) -> Result<()> {
+        let mut inner = self.inner.lock();
+        let (tracer_state, _) = inner.stopped_by(tracer)?;
+        tracer_state.options = options;
+        Ok(())
+    }
+
+    /// Returns the message about the last ptrace event.
+    pub fn event_msg(&self, tracer: &Process) -> Result<usize> {
+        let mut inner = self.inner.lock();
+        let (tracer_state, _) = inner.stopped_by(tracer)?;
+        Ok(tracer_state.event_msg)
+    }
+
+    /// Returns the information about the signal that causes the ptrace-stop.
+    pub fn siginfo(&self, tracer: &Process) -> Result<siginfo_t> {
+        let mut inner = self.inner.lock();
+        let (_, stop) = inner.stopped_by(tracer)?;
+        Ok(stop.siginfo)
+    }
+
+    /// Returns the registers.
+    pub fn regs(&self, tracer: &Process) -> Result<PtraceRegs> {
+        let mut inner = self.inner.lock();
+        let (_, stop) = inner.stopped_by(tracer)?;
+        Ok(stop.regs)
+    }
+
+    /// Sets the registers, which take effect when the thread is resumed.
+    pub fn set_regs(&self, tracer: &Process, regs: PtraceRegs) -> Result<()> {
+        let mut inner = self.inner.lock();
+        let (_, stop) = inner.stopped_by(tracer)?;
+        stop.regs = regs;
+        Ok(())
+    }
+
+    /// Reports the ptrace-stop to `tracer`, returning the wait status.
+    ///
+    /// This method returns `None` if the thread is not stopped or the stop has been reported.
+    /// If `should_consume` is false, the stop can be reported again (i.e., `WNOWAIT`).
+    pub(in crate::process) fn report_stop(
+        &self,
+        tracer: &Process,
+        should_consume: bool,
+    ) -> Option<u32> {
+        let mut inner = self.inner.lock();
+        let (_, stop) = inner.stopped_by(tracer).ok()?;
+        if stop.is_reported {
+            return None;
+        }
+
+        stop.is_reported = should_consume;
+        Some(stop.status)
+    }
+
+    // ******************* Tracee ********************
+
+    /// Enters a signal-delivery-stop if the current thread is traced.
+    ///
+    /// This method returns the signal to handle after the stop, which may be changed or
+    /// discarded by the tracer.
+    pub fn stop_at_signal(
+        &self,
+        ctx: &Context,
+        user_ctx: &mut UserContext,
+        signal: Box<dyn Signal>,
+    ) -> Option<Box<dyn Signal>> {
+        if !self.is_traced() {
+            return Some(signal);
+        }
+
+        // If the thread is killed during the stop, the signal is discarded in favor of `SIGKILL`.
+        let sig_num = signal.num();
+        let stop = self.stop(
+            ctx,
+            user_ctx,
+            StopKind::Signal(sig_num, signal.to_info()),
+            None,
+        )?;
+
+        match stop.signal {
+            Some(new_sig_num) if new_sig_num == sig_num => Some(signal),
+            Some(new_sig_num) => Some(Box::new(KernelSignal::new(new_sig_num))),
+            None => None,
+        }
+    }
+
+    /// Enters a syscall-entry-stop if the current thread is traced and is tracing syscalls.
+    ///
+    /// This method returns the number of the system call to make, which may be changed by the
+    /// tracer.
+    pub fn stop_at_syscall_entry(
+        &self,
+        ctx: &Context,
+        user_ctx: &mut UserContext,
+        syscall_num: usize,
+    ) -> usize {
+        match self.stop(ctx, user_ctx, StopKind::Syscall, Some(syscall_num)) {
+            Some(stop) => stop.regs.syscall_num(),
+            None => syscall_num,
+        }
+    }
+
+    /// Enters a syscall-exit-stop if the current thread is traced and is tracing syscalls.
+    pub fn stop_at_syscall_exit(
+        &self,
+        ctx: &Context,
+        user_ctx: &mut UserContext,
+        syscall_num: usize,
+    ) {
+        self.stop(ctx, user_ctx, StopKind::Syscall, Some(syscall_num));
+    }
+
+    /// Notifies the tracer that the current thread has executed a new program.
+    ///
+    /// If `PtraceOptions::TRACEEXEC` is set, the thread enters a `PTRACE_EVENT_EXEC` stop.
+    /// Otherwise, a `SIGTRAP` signal is sent to the thread.
+    pub fn stop_at_exec(&self, ctx: &Context, user_ctx: &mut UserContext) {
+        let is_tracing_exec = {
+            let mut inner = self.inner.lock();
+            let Some(tracer) = inner.tracer.as_mut() else {
+                return;
+            };
+            // The message is the former thread ID.
+            tracer.event_msg = ctx.posix_thread.ns_tid() as usize;
+            tracer.options.contains(PtraceOptions::TRACEEXEC)
+        };
+
+        if is_tracing_exec {
+            self.stop(ctx, user_ctx, StopKind::Exec, None);
+        } else {
+            ctx.posix_thread
+                .enqueue_signal(Box::new(KernelSignal::new(SIGTRAP)));
+        }
+    }
+
+    /// Enters a ptrace-stop and waits until the tracer resumes the current thread.
+    ///
+    /// This method returns `None` if the thread is not traced or is killed during the stop.
+    fn stop(
+        &self,
+        ctx: &Context,
+        user_ctx: &mut UserContext,
+        kind: StopKind,
+        syscall_num: Option<usize>,
+    ) -> Option<PtraceStop> {
+        let tracer_process = {
+            let mut inner = self.inner.lock();
+            let tracer = inner.tracer.as_ref()?;
+            let tracer_process = tracer.process.upgrade()?;
+
+            let (code, siginfo, signal) = match kind {
+                StopKind::Signal(sig_num, siginfo) => {
+                    (sig_num.as_u8() as u32, siginfo, Some(sig_num))
+                }
+                StopKind::Syscall => {
+                    if !tracer.is_tracing_syscalls {
+                        return None;
+                    }
+                    let mut code = SIGTRAP.as_u8() as u32;
+                    if tracer.options.contains(PtraceOptions::TRACESYSGOOD) {
+                        code |= 0x80;
+                    }
+                    (code, siginfo_t::new(SIGTRAP, code as i32), None)
+                }
+                StopKind::Exec => {
+                    let code = SIGTRAP.as_u8() as u32 | (PTRACE_EVENT_EXEC << 8);
+                    (code, siginfo_t::new(SIGTRAP, code as i32), None)
+                }
+            };
+
+            let mut regs = PtraceRegs::from_context(user_ctx, syscall_num);
+            if core::mem::take(&mut inner.is_single_stepping) {
+                regs.set_single_step(false).unwrap();
+            }
+
+            inner.stop = Some(PtraceStop {
+                status: (code << 8) | 0x7f,
+                siginfo,
+                regs,
+                signal,
+                is_reported: false,
+                is_resumed: false,
+            });
+            tracer_process
+        };
+
+        tracer_process.enqueue_signal(KernelSignal::new(SIGCHLD));
+        tracer_process.children_wait_queue().wake_all();
+        drop(tracer_process);
+
+        // Only `SIGKILL` can interrupt ptrace-stops.
+        let res = with_signal_blocked(ctx, SigSet::new_full() - SIGKILL, || {
+            self.wait_queue.pause_until(|| {
+                self.inner
+                    .lock()
+                    .stop
+                    .as_ref()
+                    .unwrap()
+                    .is_resumed
+                    .then_some(())
+            })
+        });
+
+        let stop = self.inner.lock().stop.take().unwrap();
+        if res.is_err() {
+            return None;
+        }
+
+        stop.regs.write_to_context(user_ctx);
+        Some(stop)
+    }
+
+    /// Updates the TID by which the tracer knows the thread.
+    ///
+    /// This is called when the thread takes over the TID of the main thread in `execve`.
+    pub(super) fn change_tid(&self, old_tid: Tid, new_tid: Tid) {
+        let Some(tracer_process) = self
+            .inner
+            .lock()
+            .tracer
+            .as_ref()
+            .and_then(|tracer| tracer.process.upgrade())
+        else {
+            return;
+        };
+
+        let mut tracees = tracer_process.tracees().lock();
+        if let Some(tracee) = tracees.remove(&old_tid) {
+            tracees.insert(new_tid, tracee);
+        }
+    }
+
+    /// Stops being traced because the current thread is exiting.
+    pub(super) fn detach_on_exit(&self, tid: Tid) {
+        let Some(tracer) = self.inner.lock().tracer.take() else {
+            return;
+        };
+        let Some(tracer_process) = tracer.process.upgrade() else {
+            return;
+        };
+
+        tracer_process.tracees().lock().remove(&tid);
+        // The tracer may be waiting for the thread, and should know that it is gone.
+        tracer_process.children_wait_queue().wake_all();
+    }
+}
+
+impl Inner {
+    fn is_traced_by(&self, tracer: &Process) -> bool {
+        self.tracer
+            .as_ref()
+            .is_some_and(|tracer_state| core::ptr::eq(tracer_state.process.as_ptr(), tracer))
+    }
+
+    /// Returns the tracer state and the ptrace-stop if the thread is traced by `tracer` and is
+    /// in a ptrace-stop.
+    fn stopped_by(&mut self, tracer: &Process) -> Result<(&mut Tracer, &mut PtraceStop)> {
+        if self.is_traced_by(tracer)
+            && let (Some(tracer_state), Some(stop)) = (self.tracer.as_mut(), self.stop.as_mut())
+            && !stop.is_resumed
+        {
+            return Ok((tracer_state, stop));
+        }
+
+        return_errno_with_message!(
+            Errno::ESRCH,
+            "the thread is not traced by the process or is not in a ptrace-stop"
+        );
+    }
+}
+
+impl PtraceStop {
+    fn resume(&mut self, signal: Option<SigNum>) {
+        // Similar to Linux, the signal only takes effect in signal-delivery-stops.
+        if self.signal.is_some() {
+            self.signal = signal;
+        }
+        self.is_resumed = true;
+    }
+}
+
+/// Stops tracing all the tracees of `tracer` because it is exiting.
+///
+/// The tracees in ptrace-stops are resumed, or are killed if `PtraceOptions::EXITKILL` is set.
+pub(in crate::process) fn detach_tracees(tracer: &Process) {
+    let tracees = core::mem::take(&mut *tracer.tracees().lock());
+
+    for tracee in tracees.into_values() {
+        let posix_thread = tracee.as_posix_thread().unwrap();
+        let ptrace = posix_thread.ptrace();
+
+        let mut inner = ptrace.inner.lock();
+        let Some(tracer_state) = inner.tracer.take() else {
+            continue;
+        };
+        if let Some(stop) = inner.stop.as_mut()
+            && !stop.is_resumed
+        {
+            stop.resume(None);
+        }
+        inner.is_single_stepping = false;
+        drop(inner);
+
+        ptrace.wait_queue.wake_all();
+        if tracer_state.options.contains(PtraceOptions::EXITKILL) {
+            posix_thread.enqueue_signal(Box::new(KernelSignal::new(SIGKILL)));
+        }
+    }
+}
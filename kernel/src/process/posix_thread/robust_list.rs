@@ -2,9 +2,7 @@
 
 //! The implementation of robust list is from occlum.
 
-use ostd::task::Task;
-
-use crate::{current_userspace, prelude::*, process::posix_thread::futex::futex_wake, thread::Tid};
+use crate::{current_userspace, prelude::*};
 
 #[repr(C)]
 #[derive(Clone, Copy, Debug, Pod)]
@@ -32,18 +30,23 @@ pub struct RobustListHead {
 impl RobustListHead {
     /// Return an iterator for all futexes in the robust list.
     ///
+    /// The `head_addr` is the user space address of the head. Each item contains the address of
+    /// the futex and whether the futex is a PI futex.
+    ///
     /// The futex referred to by `list_op_pending`, if any, will be returned as
     /// the last item.
-    pub fn futexes(&self) -> FutexIter<'_> {
-        FutexIter::new(self)
+    pub fn futexes(&self, head_addr: Vaddr) -> FutexIter<'_> {
+        FutexIter::new(self, head_addr)
     }
 
     /// Return the pending futex address if exist
-    fn pending_futex_addr(&self) -> Option<Vaddr> {
-        if self.list_op_pending == 0 {
+    fn pending_futex(&self) -> Option<(Vaddr, bool)> {
+        let (entry_ptr, is_pi) = split_entry_ptr(self.list_op_pending);
+        if entry_ptr == 0 {
             None
         } else {
-            self.futex_addr(self.list_op_pending)
+            self.futex_addr(entry_ptr)
+                .map(|futex_addr| (futex_addr, is_pi))
         }
     }
 
@@ -55,16 +58,25 @@ impl RobustListHead {
     }
 }
 
+/// Splits the pointer to a lock entry into the address of the entry and whether the futex is a
+/// PI futex, which is indicated by the lowest bit of the pointer.
+fn split_entry_ptr(entry_ptr: Vaddr) -> (Vaddr, bool) {
+    (entry_ptr & !1, entry_ptr & 1 != 0)
+}
+
 pub struct FutexIter<'a> {
     robust_list: &'a RobustListHead,
+    /// The address of the head, which marks the end of the list.
+    end_ptr: Vaddr,
     entry_ptr: Vaddr,
     count: isize,
 }
 
 impl<'a> FutexIter<'a> {
-    pub fn new(robust_list: &'a RobustListHead) -> Self {
+    pub fn new(robust_list: &'a RobustListHead, head_addr: Vaddr) -> Self {
         Self {
             robust_list,
+            end_ptr: head_addr,
             entry_ptr: robust_list.list.next,
             count: 0,
         }
@@ -85,77 +97,39 @@ impl<'a> FutexIter<'a> {
 const ROBUST_LIST_LIMIT: isize = 2048;
 
 impl Iterator for FutexIter<'_> {
-    type Item = Vaddr;
+    type Item = (Vaddr, bool);
 
     fn next(&mut self) -> Option<Self::Item> {
         if self.is_end() {
             return None;
         }
 
-        let end_ptr = self.robust_list.list.next;
-        while self.entry_ptr != end_ptr || self.count == 0 {
+        let (pending_ptr, _) = split_entry_ptr(self.robust_list.list_op_pending);
+        while self.entry_ptr != self.end_ptr {
             if self.count == ROBUST_LIST_LIMIT {
                 break;
             }
-            if self.entry_ptr == 0 {
+            let (entry_ptr, is_pi) = split_entry_ptr(self.entry_ptr);
+            if entry_ptr == 0 {
+                self.set_end();
                 return None;
             }
-            let futex_addr = if self.entry_ptr != self.robust_list.list_op_pending {
-                self.robust_list.futex_addr(self.entry_ptr)
+            let futex_addr = if entry_ptr != pending_ptr {
+                self.robust_list.futex_addr(entry_ptr)
             } else {
                 None
             };
-            let Ok(robust_list) = current_userspace!().read_val::<RobustList>(self.entry_ptr)
-            else {
+            let Ok(robust_list) = current_userspace!().read_val::<RobustList>(entry_ptr) else {
+                self.set_end();
                 return None;
             };
             self.entry_ptr = robust_list.next;
             self.count += 1;
-            if futex_addr.is_some() {
-                return futex_addr;
+            if let Some(futex_addr) = futex_addr {
+                return Some((futex_addr, is_pi));
             }
         }
         self.set_end();
-        self.robust_list.pending_futex_addr()
-    }
-}
-
-const FUTEX_WAITERS: u32 = 0x8000_0000;
-const FUTEX_OWNER_DIED: u32 = 0x4000_0000;
-const FUTEX_TID_MASK: u32 = 0x3FFF_FFFF;
-
-/// Wakeup one robust futex owned by the thread
-/// FIXME: requires atomic operations here
-pub fn wake_robust_futex(futex_addr: Vaddr, tid: Tid) -> Result<()> {
-    let task = Task::current().unwrap();
-    let user_space = CurrentUserSpace::new(&task);
-
-    let futex_val = {
-        if futex_addr == 0 {
-            return_errno_with_message!(Errno::EINVAL, "invalid futext addr");
-        }
-        user_space.read_val::<u32>(futex_addr)?
-    };
-    let mut old_val = futex_val;
-    loop {
-        // This futex may held by another thread, do nothing
-        if old_val & FUTEX_TID_MASK != tid {
-            break;
-        }
-        let new_val = (old_val & FUTEX_WAITERS) | FUTEX_OWNER_DIED;
-        let cur_val = user_space.read_val(futex_addr)?;
-        if cur_val != new_val {
-            // The futex value has changed, let's retry with current value
-            old_val = cur_val;
-            user_space.write_val(futex_addr, &new_val)?;
-            continue;
-        }
-        // Wakeup one waiter
-        if cur_val & FUTEX_WAITERS != 0 {
-            debug!("wake robust futex addr: {:?}", futex_addr);
-            futex_wake(futex_addr, 1, None)?;
-        }
-        break;
+        self.robust_list.pending_futex()
     }
-    Ok(())
 }
@@ -0,0 +1,304 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! Secure computing (seccomp), which restricts the system calls that a thread can make.
+//!
+//! In the strict mode, a thread can only make the `read`, `write`, `exit`, and `rt_sigreturn`
+//! system calls. In the filter mode, a thread runs a chain of classic BPF programs against every
+//! system call, and the results of the programs decide what to do with the system call.
+//!
+//! For more details, see <https://www.kernel.org/doc/html/v6.0/userspace-api/seccomp_filter.html>.
+
+use core::mem::size_of;
+
+use crate::{
+    prelude::*,
+    util::bpf::{BpfInput, BpfProgram, CSockFilter, BPF_ABS, BPF_LD, BPF_W},
+};
+
+/// The seccomp mode of a thread.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[repr(u32)]
+pub enum SeccompMode {
+    #[default]
+    Disabled = 0,
+    Strict = 1,
+    Filter = 2,
+}
+
+/// The seccomp state of a thread.
+///
+/// The state is inherited by the child threads and processes, and is preserved across `execve`.
+/// It can never be relaxed once set.
+#[derive(Debug, Clone, Default)]
+pub struct Seccomp {
+    mode: SeccompMode,
+    filter: Option<Arc<SeccompFilter>>,
+}
+
+impl Seccomp {
+    /// Returns the seccomp mode.
+    pub fn mode(&self) -> SeccompMode {
+        self.mode
+    }
+
+    /// Returns the newest filter, or `None` if the mode is not the filter mode.
+    pub fn filter(&self) -> Option<&Arc<SeccompFilter>> {
+        self.filter.as_ref()
+    }
+
+    /// Enters the strict mode.
+    ///
+    /// This method fails with `EINVAL` if the mode has been set.
+    pub fn set_strict(&mut self) -> Result<()> {
+        if self.mode != SeccompMode::Disabled {
+            return_errno_with_message!(Errno::EINVAL, "the seccomp mode has been set");
+        }
+
+        self.mode = SeccompMode::Strict;
+        Ok(())
+    }
+
+    /// Enters the filter mode and replaces the filter chain with `filter`.
+    ///
+    /// The new filter chain should contain the old one, which the caller must guarantee.
+    ///
+    /// This method fails with `EINVAL` if the thread is in the strict mode.
+    pub fn set_filter(&mut self, filter: Arc<SeccompFilter>) -> Result<()> {
+        if self.mode == SeccompMode::Strict {
+            return_errno_with_message!(Errno::EINVAL, "the thread is in the strict seccomp mode");
+        }
+
+        self.mode = SeccompMode::Filter;
+        self.filter = Some(filter);
+        Ok(())
+    }
+}
+
+/// The maximum total number of instructions in a filter chain.
+///
+/// Similar to Linux, each filter is counted with a penalty of four instructions.
+const MAX_INSNS_PER_PATH: usize = 32768;
+
+/// A seccomp filter, which is linked to the filters installed before it.
+#[derive(Debug)]
+pub struct SeccompFilter {
+    program: BpfProgram,
+    prev: Option<Arc<SeccompFilter>>,
+}
+
+impl SeccompFilter {
+    /// Creates a filter from the instructions on top of the `prev` filter chain.
+    ///
+    /// This method fails with `EINVAL` if the program is invalid, or `ENOMEM` if the filter
+    /// chain becomes too long. Besides the requirements of [`BpfProgram::new`], the program can
+    /// only load the aligned 32-bit words of [`SeccompData`].
+    pub fn new(insns: Vec<CSockFilter>, prev: Option<Arc<SeccompFilter>>) -> Result<Arc<Self>> {
+        let program = BpfProgram::new(insns, |insn| {
+            insn.code == BPF_LD | BPF_W | BPF_ABS
+                && insn.k % 4 == 0
+                && (insn.k as usize) < size_of::<SeccompData>()
+        })?;
+
+        let total_insns = program.len()
+            + prev
+                .iter()
+                .flat_map(|prev| prev.chain())
+                .map(|filter| filter.program.len() + 4)
+                .sum::<usize>();
+        if total_insns > MAX_INSNS_PER_PATH {
+            return_errno_with_message!(Errno::ENOMEM, "the seccomp filter chain is too long");
+        }
+
+        Ok(Arc::new(Self { program, prev }))
+    }
+
+    /// Runs the filter chain against the system call.
+    ///
+    /// Every filter in the chain is run, and the result with the highest precedence is returned.
+    /// If multiple filters return the action with the highest precedence, the result of the
+    /// newest filter is returned.
+    pub fn run(&self, data: &SeccompData) -> u32 {
+        self.chain()
+            .map(|filter| filter.program.run(data))
+            .min_by_key(|ret| (ret & SECCOMP_RET_ACTION_FULL) as i32)
+            .unwrap()
+    }
+
+    /// Returns whether the filter is in the filter chain of `other`.
+    pub fn is_in_chain_of(&self, other: &SeccompFilter) -> bool {
+        other.chain().any(|filter| core::ptr::eq(filter, self))
+    }
+
+    /// Returns the filter and the filters installed before it, from the newest to the oldest.
+    fn chain(&self) -> impl Iterator<Item = &SeccompFilter> {
+        core::iter::successors(Some(self), |filter| filter.prev.as_deref())
+    }
+}
+
+/// The audit architecture of the system calls (`AUDIT_ARCH_*`).
+#[cfg(target_arch = "x86_64")]
+pub const AUDIT_ARCH: u32 = 0xC000_003E;
+#[cfg(target_arch = "riscv64")]
+pub const AUDIT_ARCH: u32 = 0xC000_00F3;
+#[cfg(target_arch = "aarch64")]
+pub const AUDIT_ARCH: u32 = 0xC000_00B7;
+/// The audit architecture of the 32-bit system calls (`AUDIT_ARCH_I386`).
+#[cfg(target_arch = "x86_64")]
+pub const COMPAT_AUDIT_ARCH: u32 = 0x4000_0003;
+
+/// The system call that a seccomp filter runs against (`struct seccomp_data`).
+#[derive(Debug, Clone, Copy, Pod)]
+#[repr(C)]
+pub struct SeccompData {
+    /// The system call number
+    pub nr: i32,
+    /// The audit architecture
+    pub arch: u32,
+    /// The address of the system call instruction
+    pub instruction_pointer: u64,
+    /// The system call arguments
+    pub args: [u64; 6],
+}
+
+impl BpfInput for SeccompData {
+    fn data_len(&self) -> u32 {
+        size_of::<Self>() as u32
+    }
+
+    fn load(&self, offset: u32, size: usize) -> Option<u32> {
+        let offset = offset as usize;
+        let bytes = self.as_bytes().get(offset..offset.checked_add(size)?)?;
+        Some(u32::from_ne_bytes(bytes.try_into().ok()?))
+    }
+}
+
+const SECCOMP_RET_KILL_PROCESS: u32 = 0x8000_0000;
+const SECCOMP_RET_KILL_THREAD: u32 = 0x0000_0000;
+const SECCOMP_RET_TRAP: u32 = 0x0003_0000;
+const SECCOMP_RET_ERRNO: u32 = 0x0005_0000;
+const SECCOMP_RET_USER_NOTIF: u32 = 0x7fc0_0000;
+const SECCOMP_RET_TRACE: u32 = 0x7ff0_0000;
+const SECCOMP_RET_LOG: u32 = 0x7ffc_0000;
+const SECCOMP_RET_ALLOW: u32 = 0x7fff_0000;
+
+const SECCOMP_RET_ACTION_FULL: u32 = 0xffff_0000;
+const SECCOMP_RET_DATA: u32 = 0x0000_ffff;
+
+/// The action that a seccomp filter takes on a system call.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SeccompAction {
+    /// Kills the process.
+    KillProcess,
+    /// Kills the thread.
+    KillThread,
+    /// Sends a `SIGSYS` signal with the data in `si_errno`.
+    Trap(u16),
+    /// Returns the data as the error number.
+    Errno(u16),
+    /// Notifies the user-space supervisor.
+    UserNotif,
+    /// Notifies the tracer.
+    Trace,
+    /// Allows the system call after logging it.
+    Log,
+    /// Allows the system call.
+    Allow,
+}
+
+impl SeccompAction {
+    /// Parses the result of a seccomp filter.
+    pub fn from_ret(ret: u32) -> Self {
+        let data = (ret & SECCOMP_RET_DATA) as u16;
+
+        match ret & SECCOMP_RET_ACTION_FULL {
+            SECCOMP_RET_KILL_PROCESS => Self::KillProcess,
+            SECCOMP_RET_TRAP => Self::Trap(data),
+            SECCOMP_RET_ERRNO => Self::Errno(data),
+            SECCOMP_RET_USER_NOTIF => Self::UserNotif,
+            SECCOMP_RET_TRACE => Self::Trace,
+            SECCOMP_RET_LOG => Self::Log,
+            SECCOMP_RET_ALLOW => Self::Allow,
+            // Unknown actions are treated as `SECCOMP_RET_KILL_THREAD`, which is the same as
+            // Linux.
+            _ => Self::KillThread,
+        }
+    }
+
+    /// Returns whether the action is known, as is checked by `SECCOMP_GET_ACTION_AVAIL`.
+    pub fn is_available(action: u32) -> bool {
+        matches!(
+            action,
+            SECCOMP_RET_KILL_PROCESS
+                | SECCOMP_RET_KILL_THREAD
+                | SECCOMP_RET_TRAP
+                | SECCOMP_RET_ERRNO
+                | SECCOMP_RET_USER_NOTIF
+                | SECCOMP_RET_TRACE
+                | SECCOMP_RET_LOG
+                | SECCOMP_RET_ALLOW
+        )
+    }
+}
+
+#[cfg(ktest)]
+mod test {
+    use ostd::prelude::*;
+
+    use super::*;
+    use crate::util::bpf::{BPF_JEQ, BPF_JMP, BPF_K, BPF_RET};
+
+    const fn insn(code: u16, jt: u8, jf: u8, k: u32) -> CSockFilter {
+        CSockFilter { code, jt, jf, k }
+    }
+
+    const fn data(nr: i32) -> SeccompData {
+        SeccompData {
+            nr,
+            arch: AUDIT_ARCH,
+            instruction_pointer: 0,
+            args: [0; 6],
+        }
+    }
+
+    /// Returns the program that returns `ret` for the system call `nr` and allows the others.
+    fn program(nr: u32, ret: u32) -> Vec<CSockFilter> {
+        vec![
+            insn(BPF_LD | BPF_W | BPF_ABS, 0, 0, 0),
+            insn(BPF_JMP | BPF_JEQ | BPF_K, 0, 1, nr),
+            insn(BPF_RET | BPF_K, 0, 0, ret),
+            insn(BPF_RET | BPF_K, 0, 0, SECCOMP_RET_ALLOW),
+        ]
+    }
+
+    #[ktest]
+    fn test_chain() {
+        let first = SeccompFilter::new(program(1, SECCOMP_RET_ERRNO | 1), None).unwrap();
+        assert_eq!(first.run(&data(1)), SECCOMP_RET_ERRNO | 1);
+        assert_eq!(first.run(&data(2)), SECCOMP_RET_ALLOW);
+
+        // The action with the highest precedence wins, regardless of the order.
+        let second = SeccompFilter::new(program(1, SECCOMP_RET_TRAP), Some(first.clone())).unwrap();
+        assert_eq!(second.run(&data(1)), SECCOMP_RET_TRAP);
+
+        // If the actions are the same, the newest filter wins.
+        let third =
+            SeccompFilter::new(program(1, SECCOMP_RET_TRAP | 2), Some(second.clone())).unwrap();
+        assert_eq!(third.run(&data(1)), SECCOMP_RET_TRAP | 2);
+
+        assert!(first.is_in_chain_of(&third));
+        assert!(!third.is_in_chain_of(&first));
+    }
+
+    #[ktest]
+    fn test_invalid() {
+        // Unaligned loads
+        let mut insns = program(1, SECCOMP_RET_KILL_THREAD);
+        insns[0].k = 2;
+        assert!(SeccompFilter::new(insns, None).is_err());
+
+        // Out-of-bounds loads
+        let mut insns = program(1, SECCOMP_RET_KILL_THREAD);
+        insns[0].k = size_of::<SeccompData>() as u32;
+        assert!(SeccompFilter::new(insns, None).is_err());
+    }
+}
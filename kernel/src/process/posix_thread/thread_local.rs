@@ -1,11 +1,21 @@
 // SPDX-License-Identifier: MPL-2.0
 
-use core::cell::{Cell, RefCell};
-
-use ostd::{mm::Vaddr, sync::RwArc, task::CurrentTask};
-
-use super::RobustListHead;
-use crate::{fs::file_table::FileTable, process::signal::SigStack};
+use core::cell::{Cell, Ref, RefCell};
+
+use aster_rights::Full;
+use ostd::{
+    mm::Vaddr,
+    sync::{RwArc, Waker},
+    task::CurrentTask,
+};
+
+use crate::{
+    fs::{file_table::FileTable, thread_info::ThreadFsInfo},
+    prelude::*,
+    process::{signal::SigStack, ProcessVm},
+    security::audit::AuditContext,
+    vm::vmar::Vmar,
+};
 
 /// Local data for a POSIX thread.
 pub struct ThreadLocal {
@@ -16,10 +26,19 @@ pub struct ThreadLocal {
 
     // Robust futexes.
     // https://man7.org/linux/man-pages/man2/get_robust_list.2.html
-    robust_list: RefCell<Option<RobustListHead>>,
+    robust_list: Cell<Vaddr>,
+
+    // vfork.
+    /// The waker of the parent that is suspended by `vfork`.
+    vfork_done: RefCell<Option<Arc<Waker>>>,
+
+    // Virtual memory.
+    /// The virtual memory of the process, which is cached to be accessed without locking.
+    process_vm: RefCell<Arc<ProcessVm>>,
 
     // Files.
     file_table: RefCell<RwArc<FileTable>>,
+    fs: RefCell<Arc<ThreadFsInfo>>,
 
     // Signal.
     /// `ucontext` address for the signal handler.
@@ -27,22 +46,39 @@ pub struct ThreadLocal {
     // `sig_context` is always equals with RSP.
     sig_context: Cell<Option<Vaddr>>,
     /// Stack address, size, and flags for the signal handler.
-    sig_stack: RefCell<Option<SigStack>>,
+    sig_stack: RefCell<SigStack>,
+
+    // Profiling.
+    /// Whether the user-mode code is sampled, but its call chain has not been unwound.
+    has_pending_user_sample: Cell<bool>,
+
+    // Auditing.
+    /// The audit context of the current system call, if it is audited.
+    audit_context: RefCell<Option<AuditContext>>,
 }
 
 impl ThreadLocal {
     pub(super) fn new(
         set_child_tid: Vaddr,
         clear_child_tid: Vaddr,
+        vfork_done: Option<Arc<Waker>>,
+        process_vm: Arc<ProcessVm>,
         file_table: RwArc<FileTable>,
+        fs: Arc<ThreadFsInfo>,
+        sig_stack: SigStack,
     ) -> Self {
         Self {
             set_child_tid: Cell::new(set_child_tid),
             clear_child_tid: Cell::new(clear_child_tid),
-            robust_list: RefCell::new(None),
+            robust_list: Cell::new(0),
+            vfork_done: RefCell::new(vfork_done),
+            process_vm: RefCell::new(process_vm),
             file_table: RefCell::new(file_table),
+            fs: RefCell::new(fs),
             sig_context: Cell::new(None),
-            sig_stack: RefCell::new(None),
+            sig_stack: RefCell::new(sig_stack),
+            has_pending_user_sample: Cell::new(false),
+            audit_context: RefCell::new(None),
         }
     }
 
@@ -54,21 +90,87 @@ impl ThreadLocal {
         &self.clear_child_tid
     }
 
-    pub fn robust_list(&self) -> &RefCell<Option<RobustListHead>> {
+    pub fn robust_list(&self) -> &Cell<Vaddr> {
         &self.robust_list
     }
 
+    /// Wakes up the parent that is suspended by `vfork`, if any.
+    pub fn wake_vfork_parent(&self) {
+        if let Some(waker) = self.vfork_done.borrow_mut().take() {
+            waker.wake_up();
+        }
+    }
+
+    /// Borrows the virtual memory of the process.
+    ///
+    /// This is faster than [`Process::vm`], since the virtual memory is cached in the
+    /// thread-local data. It can only be replaced by [`Self::set_process_vm`].
+    ///
+    /// [`Process::vm`]: crate::process::Process::vm
+    pub fn process_vm(&self) -> Ref<'_, Arc<ProcessVm>> {
+        self.process_vm.borrow()
+    }
+
+    /// Borrows the root VMAR of the process.
+    ///
+    /// This is faster than [`Process::root_vmar`], which should only be used to access the
+    /// virtual memory of other processes.
+    ///
+    /// [`Process::root_vmar`]: crate::process::Process::root_vmar
+    pub fn root_vmar(&self) -> Ref<'_, Vmar<Full>> {
+        Ref::map(self.process_vm.borrow(), |process_vm| {
+            process_vm.root_vmar()
+        })
+    }
+
+    /// Replaces the cached virtual memory of the process.
+    ///
+    /// This should only be called by `execve` after the other threads of the process have
+    /// exited, since their cached virtual memory is not replaced.
+    ///
+    /// # Panics
+    ///
+    /// This method panics if the virtual memory is borrowed.
+    pub fn set_process_vm(&self, process_vm: Arc<ProcessVm>) {
+        *self.process_vm.borrow_mut() = process_vm;
+    }
+
     pub fn file_table(&self) -> &RefCell<RwArc<FileTable>> {
         &self.file_table
     }
 
+    /// Borrows the FS information of the thread.
+    ///
+    /// The FS information is only accessed by the thread itself, so it lives in the thread-local
+    /// data and can be replaced by [`Self::set_fs`].
+    pub fn borrow_fs(&self) -> Ref<'_, Arc<ThreadFsInfo>> {
+        self.fs.borrow()
+    }
+
+    /// Replaces the FS information of the thread.
+    ///
+    /// # Panics
+    ///
+    /// This method panics if the FS information is borrowed.
+    pub fn set_fs(&self, fs: Arc<ThreadFsInfo>) {
+        *self.fs.borrow_mut() = fs;
+    }
+
     pub fn sig_context(&self) -> &Cell<Option<Vaddr>> {
         &self.sig_context
     }
 
-    pub fn sig_stack(&self) -> &RefCell<Option<SigStack>> {
+    pub fn sig_stack(&self) -> &RefCell<SigStack> {
         &self.sig_stack
     }
+
+    pub fn has_pending_user_sample(&self) -> &Cell<bool> {
+        &self.has_pending_user_sample
+    }
+
+    pub fn audit_context(&self) -> &RefCell<Option<AuditContext>> {
+        &self.audit_context
+    }
 }
 
 /// A trait to provide the `as_thread_local` method for tasks.
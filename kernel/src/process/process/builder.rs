@@ -6,6 +6,8 @@ use super::{Pid, Process};
 use crate::{
     prelude::*,
     process::{
+        cgroup::Cgroup,
+        namespace::PidNamespace,
         posix_thread::{create_posix_task_from_executable, PosixThreadBuilder},
         process_vm::ProcessVm,
         rlimit::ResourceLimits,
@@ -25,11 +27,13 @@ pub struct ProcessBuilder<'a> {
     main_thread_builder: Option<PosixThreadBuilder>,
     argv: Option<Vec<CString>>,
     envp: Option<Vec<CString>>,
-    process_vm: Option<ProcessVm>,
+    process_vm: Option<Arc<ProcessVm>>,
     resource_limits: Option<ResourceLimits>,
     sig_dispositions: Option<Arc<Mutex<SigDispositions>>>,
     credentials: Option<Credentials>,
     nice: Option<Nice>,
+    cgroup: Option<Arc<Cgroup>>,
+    pid_ns: Option<Arc<PidNamespace>>,
 }
 
 impl<'a> ProcessBuilder<'a> {
@@ -46,6 +50,8 @@ impl<'a> ProcessBuilder<'a> {
             sig_dispositions: None,
             credentials: None,
             nice: None,
+            cgroup: None,
+            pid_ns: None,
         }
     }
 
@@ -54,7 +60,7 @@ impl<'a> ProcessBuilder<'a> {
         self
     }
 
-    pub fn process_vm(&mut self, process_vm: ProcessVm) -> &mut Self {
+    pub fn process_vm(&mut self, process_vm: Arc<ProcessVm>) -> &mut Self {
         self.process_vm = Some(process_vm);
         self
     }
@@ -89,6 +95,17 @@ impl<'a> ProcessBuilder<'a> {
         self
     }
 
+    pub fn cgroup(&mut self, cgroup: Arc<Cgroup>) -> &mut Self {
+        self.cgroup = Some(cgroup);
+        self
+    }
+
+    /// Sets the PID namespace, where the IDs of the process must have been allocated.
+    pub fn pid_ns(&mut self, pid_ns: Arc<PidNamespace>) -> &mut Self {
+        self.pid_ns = Some(pid_ns);
+        self
+    }
+
     fn check_build(&self) -> Result<()> {
         if self.main_thread_builder.is_some() {
             debug_assert!(self.parent.upgrade().is_some());
@@ -121,9 +138,11 @@ impl<'a> ProcessBuilder<'a> {
             sig_dispositions,
             credentials,
             nice,
+            cgroup,
+            pid_ns,
         } = self;
 
-        let process_vm = process_vm.or_else(|| Some(ProcessVm::alloc())).unwrap();
+        let process_vm = process_vm.unwrap_or_else(|| Arc::new(ProcessVm::alloc()));
 
         let resource_limits = resource_limits
             .or_else(|| Some(ResourceLimits::default()))
@@ -135,13 +154,19 @@ impl<'a> ProcessBuilder<'a> {
 
         let nice = nice.or_else(|| Some(Nice::default())).unwrap();
 
+        let cgroup = cgroup.unwrap_or_else(|| Cgroup::root().clone());
+
+        let pid_ns = pid_ns.unwrap_or_else(|| PidNamespace::get_init_singleton().clone());
+
         let process = Process::new(
             pid,
+            pid_ns,
             parent,
             executable_path.to_string(),
             process_vm,
             resource_limits,
             nice,
+            cgroup,
             sig_dispositions,
         );
 
@@ -152,7 +177,7 @@ impl<'a> ProcessBuilder<'a> {
             create_posix_task_from_executable(
                 pid,
                 credentials.unwrap(),
-                process.vm(),
+                &process.vm(),
                 executable_path,
                 Arc::downgrade(&process),
                 argv.unwrap(),
@@ -1,14 +1,13 @@
 // SPDX-License-Identifier: MPL-2.0
 
-#![allow(unused_variables)]
-
-use ostd::sync::WaitQueue;
-
 use crate::{
     prelude::*,
     process::{
+        posix_thread::AsPosixThread,
         signal::{
-            constants::{SIGCONT, SIGHUP},
+            constants::{SIGCONT, SIGHUP, SIGTTIN, SIGTTOU},
+            sig_action::SigAction,
+            sig_num::SigNum,
             signals::kernel::KernelSignal,
         },
         ProcessGroup, Session,
@@ -23,7 +22,6 @@ use crate::{
 pub struct JobControl {
     foreground: SpinLock<Weak<ProcessGroup>>,
     session: SpinLock<Weak<Session>>,
-    wait_queue: WaitQueue,
 }
 
 impl JobControl {
@@ -32,7 +30,6 @@ impl JobControl {
         Self {
             foreground: SpinLock::new(Weak::new()),
             session: SpinLock::new(Weak::new()),
-            wait_queue: WaitQueue::new(),
         }
     }
 
@@ -74,24 +71,26 @@ impl JobControl {
         let session = current.session().unwrap();
         *self.session.lock() = Arc::downgrade(&session);
 
-        self.wait_queue.wake_all();
         Ok(())
     }
 
     /// Releases the current session from this terminal.
     pub fn release_current_session(&self) -> Result<()> {
-        let Some(session) = self.session() else {
+        if self.session().is_none() {
             return_errno_with_message!(
                 Errno::ENOTTY,
                 "the terminal is not controlling terminal now"
             );
-        };
+        }
 
         if let Some(foreground) = self.foreground() {
             foreground.broadcast_signal(KernelSignal::new(SIGHUP));
             foreground.broadcast_signal(KernelSignal::new(SIGCONT));
         }
 
+        *self.foreground.lock() = Weak::new();
+        *self.session.lock() = Weak::new();
+
         Ok(())
     }
 
@@ -107,13 +106,7 @@ impl JobControl {
     /// # Panics
     ///
     /// The process group should belong to one session.
-    pub fn set_foreground(&self, process_group: Option<&Arc<ProcessGroup>>) -> Result<()> {
-        let Some(process_group) = process_group else {
-            // FIXME: should we allow this branch?
-            *self.foreground.lock() = Weak::new();
-            return Ok(());
-        };
-
+    pub fn set_foreground(&self, process_group: &Arc<ProcessGroup>) -> Result<()> {
         let session = process_group.session().unwrap();
         let Some(terminal_session) = self.session() else {
             return_errno_with_message!(
@@ -130,38 +123,84 @@ impl JobControl {
         }
 
         *self.foreground.lock() = Arc::downgrade(process_group);
-        self.wait_queue.wake_all();
         Ok(())
     }
 
-    /// Wait until the current process is the foreground process group. If
-    /// the foreground process group is None, returns true.
+    // *************** Background process group ***************
+
+    /// Checks whether the current process can read from the terminal.
+    ///
+    /// If the current process belongs to a background process group in the session of the
+    /// terminal, `SIGTTIN` is sent to the process group and this method returns `ERESTARTSYS`,
+    /// so that the read is restarted after the process group is continued. If the signal is
+    /// ignored or blocked, or the process group is orphaned, this method returns `EIO`.
     ///
     /// # Panics
     ///
-    /// This function should only be called in process context.
-    pub fn wait_until_in_foreground(&self) -> Result<()> {
-        // Fast path
-        if self.current_belongs_to_foreground() {
+    /// This method should only be called in process context.
+    pub fn check_read(&self) -> Result<()> {
+        self.check_background_access(SIGTTIN)
+    }
+
+    /// Checks whether the current process can write to the terminal or change its attributes.
+    ///
+    /// This is similar to [`Self::check_read`], except that `SIGTTOU` is sent and that the
+    /// current process is allowed to proceed if the signal is ignored or blocked.
+    ///
+    /// # Panics
+    ///
+    /// This method should only be called in process context.
+    pub fn check_write(&self) -> Result<()> {
+        self.check_background_access(SIGTTOU)
+    }
+
+    fn check_background_access(&self, sig_num: SigNum) -> Result<()> {
+        let current = current!();
+        let Some(process_group) = current.process_group() else {
+            return Ok(());
+        };
+        if !self.is_background(&process_group) {
             return Ok(());
         }
 
-        // Slow path
-        self.wait_queue.pause_until(|| {
-            if self.current_belongs_to_foreground() {
-                Some(())
-            } else {
-                None
+        let is_ignored = current.sig_dispositions().lock().get(sig_num) == SigAction::Ign;
+        let is_blocked = current_thread!()
+            .as_posix_thread()
+            .unwrap()
+            .has_signal_blocked(sig_num);
+        if is_ignored || is_blocked {
+            if sig_num == SIGTTOU {
+                return Ok(());
             }
-        })
+            return_errno_with_message!(
+                Errno::EIO,
+                "the background process cannot read from the terminal"
+            );
+        }
+
+        if process_group.is_orphaned() {
+            return_errno_with_message!(Errno::EIO, "the orphaned process group cannot be stopped");
+        }
+
+        process_group.broadcast_signal(KernelSignal::new(sig_num));
+        return_errno_with_message!(
+            Errno::ERESTARTSYS,
+            "the background process group is stopped"
+        );
     }
 
-    fn current_belongs_to_foreground(&self) -> bool {
-        let Some(foreground) = self.foreground() else {
-            return true;
+    /// Returns whether the process group is a background process group in the session of the
+    /// terminal.
+    fn is_background(&self, process_group: &Arc<ProcessGroup>) -> bool {
+        let Some(session) = self.session() else {
+            return false;
         };
+        if !session.contains_process_group(process_group) {
+            return false;
+        }
 
-        foreground.contains_process(current!().pid())
+        self.foreground()
+            .is_some_and(|foreground| !Arc::ptr_eq(&foreground, process_group))
     }
 }
 
@@ -1,17 +1,27 @@
 // SPDX-License-Identifier: MPL-2.0
 
-use core::sync::atomic::{AtomicU32, Ordering};
+use core::{
+    ops::Range,
+    sync::atomic::{AtomicBool, AtomicI16, AtomicU32, AtomicUsize, Ordering},
+};
 
 use self::timer_manager::PosixTimerManager;
 use super::{
+    cgroup::Cgroup,
+    credentials::capabilities::CapSet,
+    namespace::PidNamespace,
     posix_thread::{allocate_posix_tid, AsPosixThread},
     process_table,
-    process_vm::{Heap, InitStackReader, ProcessVm},
-    rlimit::ResourceLimits,
+    process_vm::ProcessVm,
+    rlimit::{ResourceLimits, ResourceType, RLIM_INFINITY},
     signal::{
+        constants::SIGCHLD,
+        sig_action::{SigAction, SigActionFlags},
         sig_disposition::SigDispositions,
         sig_num::{AtomicSigNum, SigNum},
-        signals::Signal,
+        sig_queues::SigQueues,
+        signals::{kernel::KernelSignal, Signal},
+        Pollee, SigEvents, SigEventsFilter,
     },
     status::ProcessStatus,
     task_set::TaskSet,
@@ -19,9 +29,13 @@ use super::{
 };
 use crate::{
     device::tty::open_ntty_as_controlling_terminal,
+    events::Observer,
     prelude::*,
-    sched::priority::{AtomicNice, Nice},
-    thread::{AsThread, Thread},
+    sched::{
+        cpuset::Cpuset,
+        priority::{AtomicNice, Nice},
+    },
+    thread::{AsThread, Thread, Tid},
     time::clocks::ProfClock,
     vm::vmar::Vmar,
 };
@@ -37,10 +51,11 @@ use aster_rights::Full;
 use atomic_integer_wrapper::define_atomic_version_of_integer_like_type;
 pub use builder::ProcessBuilder;
 pub use job_control::JobControl;
-use ostd::{sync::WaitQueue, task::Task};
+use ostd::{mm::MAX_USERSPACE_VADDR, sync::WaitQueue, task::Task};
 pub use process_group::ProcessGroup;
 pub use session::Session;
 pub use terminal::Terminal;
+pub use timer_manager::PosixTimer;
 
 /// Process id.
 pub type Pid = u32;
@@ -62,13 +77,17 @@ pub(super) fn init() {
 /// Process stands for a set of threads that shares the same userspace.
 pub struct Process {
     // Immutable Part
+    /// The global PID.
     pid: Pid,
+    /// The PID namespace.
+    pid_ns: Arc<PidNamespace>,
 
-    process_vm: ProcessVm,
     /// Wait for child status changed
     children_wait_queue: WaitQueue,
 
     // Mutable Part
+    /// The virtual memory, which may be shared with other processes (see `CLONE_VM`).
+    process_vm: SpinLock<Arc<ProcessVm>>,
     /// The executable path.
     executable_path: RwLock<String>,
     /// The threads
@@ -79,6 +98,10 @@ pub struct Process {
     pub(super) parent: ParentProcess,
     /// Children processes
     children: Mutex<BTreeMap<Pid, Arc<Process>>>,
+    /// Whether the process adopts the orphaned descendants (see `PR_SET_CHILD_SUBREAPER`).
+    is_child_subreaper: AtomicBool,
+    /// The threads traced by the process
+    tracees: Mutex<BTreeMap<Tid, Arc<Thread>>>,
     /// Process group
     pub(super) process_group: Mutex<Weak<ProcessGroup>>,
     /// resource limits
@@ -87,16 +110,42 @@ pub struct Process {
     /// According to POSIX.1, the nice value is a per-process attribute,
     /// the threads in a process should share a nice value.
     nice: AtomicNice,
+    /// The cgroup that the process belongs to.
+    cgroup: Mutex<Arc<Cgroup>>,
 
     // Signal
     /// Sig dispositions
     sig_dispositions: Arc<Mutex<SigDispositions>>,
+    /// The pending signals that are directed at the process as a whole.
+    ///
+    /// Any thread that does not block such a signal may dequeue it.
+    sig_queues: SigQueues,
     /// The signal that the process should receive when parent process exits.
     parent_death_signal: AtomicSigNum,
 
     /// The signal that should be sent to the parent when this process exits.
     exit_signal: AtomicSigNum,
 
+    /// Whether the process can be dumped into a core file.
+    is_dumpable: AtomicBool,
+
+    /// Whether the process has called `execve` since it was created by `fork`.
+    has_called_execve: AtomicBool,
+
+    /// The personality of the process (see `personality`).
+    personality: AtomicU32,
+
+    /// The adjustment to the badness score when the OOM killer selects a victim.
+    oom_score_adj: AtomicI16,
+
+    /// The size of the memory that is pinned by the kernel (e.g., for I/O) in bytes.
+    ///
+    /// Like the locked mappings, the pinned memory is limited by `RLIMIT_MEMLOCK`.
+    pinned_size: AtomicUsize,
+
+    /// The pollee that notifies the pidfds referring to the process when the process exits.
+    pidfd_pollee: Pollee,
+
     /// A profiling clock measures the user CPU time and kernel CPU time of the current process.
     prof_clock: Arc<ProfClock>,
 
@@ -174,12 +223,14 @@ impl Process {
     #[allow(clippy::too_many_arguments)]
     fn new(
         pid: Pid,
+        pid_ns: Arc<PidNamespace>,
         parent: Weak<Process>,
         executable_path: String,
-        process_vm: ProcessVm,
+        process_vm: Arc<ProcessVm>,
 
         resource_limits: ResourceLimits,
         nice: Nice,
+        cgroup: Arc<Cgroup>,
         sig_dispositions: Arc<Mutex<SigDispositions>>,
     ) -> Arc<Self> {
         // SIGCHID does not interrupt pauser. Child process will
@@ -187,25 +238,40 @@ impl Process {
         let children_wait_queue = WaitQueue::new();
 
         let prof_clock = ProfClock::new();
+        let cpu_limit = *resource_limits.get_rlimit(ResourceType::RLIMIT_CPU);
 
-        Arc::new_cyclic(|process_ref: &Weak<Process>| Self {
+        let process = Arc::new_cyclic(|process_ref: &Weak<Process>| Self {
             pid,
+            pid_ns,
             tasks: Mutex::new(TaskSet::new()),
             executable_path: RwLock::new(executable_path),
-            process_vm,
+            process_vm: SpinLock::new(process_vm),
             children_wait_queue,
             status: ProcessStatus::default(),
             parent: ParentProcess::new(parent),
             children: Mutex::new(BTreeMap::new()),
+            is_child_subreaper: AtomicBool::new(false),
+            tracees: Mutex::new(BTreeMap::new()),
             process_group: Mutex::new(Weak::new()),
             sig_dispositions,
+            sig_queues: SigQueues::new(),
             parent_death_signal: AtomicSigNum::new_empty(),
             exit_signal: AtomicSigNum::new_empty(),
+            is_dumpable: AtomicBool::new(true),
+            has_called_execve: AtomicBool::new(false),
+            personality: AtomicU32::new(0),
+            oom_score_adj: AtomicI16::new(0),
+            pinned_size: AtomicUsize::new(0),
+            pidfd_pollee: Pollee::new(),
             resource_limits: Mutex::new(resource_limits),
             nice: AtomicNice::new(nice),
+            cgroup: Mutex::new(cgroup),
             timer_manager: PosixTimerManager::new(&prof_clock, process_ref),
             prof_clock,
-        })
+        });
+        process.timer_manager.set_cpu_limit(&cpu_limit);
+
+        process
     }
 
     /// init a user process and run the process
@@ -278,10 +344,24 @@ impl Process {
 
     // *********** Basic structures ***********
 
+    /// Returns the global PID of the process.
+    ///
+    /// The global PID is the PID in the initial PID namespace. Use [`Self::pid_ns`] to translate
+    /// it before showing it to the user space.
     pub fn pid(&self) -> Pid {
         self.pid
     }
 
+    /// Returns the PID namespace of the process.
+    pub fn pid_ns(&self) -> &Arc<PidNamespace> {
+        &self.pid_ns
+    }
+
+    /// Returns the PID of the process in its PID namespace.
+    pub fn ns_pid(&self) -> Pid {
+        self.pid_ns.local_id(self.pid).unwrap()
+    }
+
     /// Gets the profiling clock of the process.
     pub fn prof_clock(&self) -> &Arc<ProfClock> {
         &self.prof_clock
@@ -312,6 +392,23 @@ impl Process {
         &self.nice
     }
 
+    /// Returns the cgroup that the process belongs to.
+    pub fn cgroup(&self) -> Arc<Cgroup> {
+        self.cgroup.lock().clone()
+    }
+
+    /// Sets the cgroup that the process belongs to.
+    ///
+    /// This should only be called by [`Cgroup::attach`].
+    pub(super) fn set_cgroup(&self, cgroup: Arc<Cgroup>) {
+        *self.cgroup.lock() = cgroup;
+    }
+
+    /// Returns the cpuset that the process belongs to.
+    pub fn cpuset(&self) -> Arc<Cpuset> {
+        self.cgroup().effective_cpuset().clone()
+    }
+
     pub fn main_thread(&self) -> Arc<Thread> {
         self.tasks.lock().main().as_thread().unwrap().clone()
     }
@@ -321,8 +418,9 @@ impl Process {
         &self.parent
     }
 
+    /// Returns whether the process is the init process of its PID namespace.
     pub fn is_init_process(&self) -> bool {
-        self.parent.lock().process().upgrade().is_none()
+        self.pid_ns.init_process_id() == Some(self.pid)
     }
 
     pub(super) fn children(&self) -> &Mutex<BTreeMap<Pid, Arc<Process>>> {
@@ -337,6 +435,26 @@ impl Process {
         &self.children_wait_queue
     }
 
+    /// Returns whether the process is a child subreaper.
+    ///
+    /// A child subreaper adopts the orphaned descendants in place of the init process.
+    pub fn is_child_subreaper(&self) -> bool {
+        self.is_child_subreaper.load(Ordering::Relaxed)
+    }
+
+    /// Sets whether the process is a child subreaper.
+    pub fn set_child_subreaper(&self, is_child_subreaper: bool) {
+        self.is_child_subreaper
+            .store(is_child_subreaper, Ordering::Relaxed);
+    }
+
+    /// Returns the threads traced by the process.
+    ///
+    /// The tracees are waited for in the same way as the children.
+    pub(super) fn tracees(&self) -> &Mutex<BTreeMap<Tid, Arc<Thread>>> {
+        &self.tracees
+    }
+
     // *********** Process group & Session***********
 
     /// Returns the process group ID of the process.
@@ -385,17 +503,18 @@ impl Process {
 
     /// Moves the process to the new session.
     ///
-    /// If the process is already session leader, this method does nothing.
-    ///
-    /// Otherwise, this method creates a new process group in a new session
-    /// and moves the process to the session, returning the new session.
+    /// This method creates a new process group in a new session and moves the process to the
+    /// session, returning the new session. The new session has no controlling terminal.
     ///
     /// This method may return the following errors:
-    ///  * `EPERM`, if the process is a process group leader, or some existing session
-    ///    or process group has the same ID as the process.
+    ///  * `EPERM`, if the process is a process group leader (including a session leader), or
+    ///    some existing session or process group has the same ID as the process.
     pub fn to_new_session(self: &Arc<Self>) -> Result<Arc<Session>> {
         if self.is_session_leader() {
-            return Ok(self.session().unwrap());
+            return_errno_with_message!(
+                Errno::EPERM,
+                "session leader cannot be moved to new session."
+            );
         }
 
         if self.is_group_leader() {
@@ -468,15 +587,15 @@ impl Process {
     ///  * The group already exists, but the group does not belong to the same session as the process;
     ///  * The group does not exist, but `pgid` is not equal to `pid` of the process.
     pub fn to_other_group(self: &Arc<Self>, pgid: Pgid) -> Result<()> {
+        if self.is_session_leader() {
+            return_errno_with_message!(Errno::EPERM, "the process cannot be a session leader");
+        }
+
         // if the process already belongs to the process group
         if self.pgid() == pgid {
             return Ok(());
         }
 
-        if self.is_session_leader() {
-            return_errno_with_message!(Errno::EPERM, "the process cannot be a session leader");
-        }
-
         if let Some(process_group) = process_table::get_process_group(&pgid) {
             let session = self.session().unwrap();
             if !session.contains_process_group(&process_group) {
@@ -567,6 +686,11 @@ impl Process {
 
             if old_group_inner.is_empty() {
                 group_table_mut.remove(&old_group.pgid());
+                // The session won't be empty, since the process is moved to another group of the
+                // session.
+                if let Some(session) = old_group_inner.session.upgrade() {
+                    session.inner.lock().remove_process_group(&old_group.pgid());
+                }
             }
 
             group_inner
@@ -583,20 +707,107 @@ impl Process {
 
     // ************** Virtual Memory *************
 
-    pub fn vm(&self) -> &ProcessVm {
-        &self.process_vm
+    /// Returns the virtual memory of the process.
+    ///
+    /// For the current process, [`ThreadLocal::process_vm`] is faster.
+    ///
+    /// [`ThreadLocal::process_vm`]: crate::process::posix_thread::ThreadLocal::process_vm
+    pub fn vm(&self) -> Arc<ProcessVm> {
+        self.process_vm.lock().clone()
+    }
+
+    /// Replaces the virtual memory of the process with `process_vm`.
+    ///
+    /// The old virtual memory is not cleared, since it may still be used by other processes
+    /// (e.g., the parent process suspended by `vfork`).
+    pub fn set_vm(&self, process_vm: Arc<ProcessVm>) {
+        *self.process_vm.lock() = process_vm;
+    }
+
+    /// Returns the root VMAR of the process.
+    ///
+    /// For the current process, [`ThreadLocal::root_vmar`] is faster.
+    ///
+    /// [`ThreadLocal::root_vmar`]: crate::process::posix_thread::ThreadLocal::root_vmar
+    pub fn root_vmar(&self) -> Vmar<Full> {
+        self.vm().root_vmar().dup().unwrap()
+    }
+
+    /// Checks whether `len` bytes can be added to the address space without exceeding
+    /// `RLIMIT_AS`.
+    ///
+    /// The mappings in `replaced_range`, if any, are going to be replaced, so they are not
+    /// counted.
+    pub fn check_address_space_limit(
+        &self,
+        len: usize,
+        replaced_range: Option<Range<Vaddr>>,
+    ) -> Result<()> {
+        let limit = self
+            .resource_limits
+            .lock()
+            .get_rlimit(ResourceType::RLIMIT_AS)
+            .get_cur();
+        if limit == RLIM_INFINITY {
+            return Ok(());
+        }
+
+        let replaced_size = replaced_range.map_or(0, |range| self.root_vmar().mapped_size(range));
+        let new_size = self.vm().address_space_size() - replaced_size + len;
+        if new_size as u64 > limit {
+            return_errno_with_message!(Errno::ENOMEM, "the address space limit is exceeded");
+        }
+        Ok(())
+    }
+
+    /// Checks whether `len` more bytes can be locked in the memory without exceeding
+    /// `RLIMIT_MEMLOCK`.
+    ///
+    /// The limit does not apply to the threads with `CAP_IPC_LOCK`.
+    pub fn check_locked_memory_limit(&self, len: usize) -> Result<()> {
+        let current = current_thread!();
+        let credentials = current.as_posix_thread().unwrap().credentials();
+        if credentials.effective_capset().contains(CapSet::IPC_LOCK) {
+            return Ok(());
+        }
+
+        let limit = self
+            .resource_limits
+            .lock()
+            .get_rlimit(ResourceType::RLIMIT_MEMLOCK)
+            .get_cur();
+        if limit == RLIM_INFINITY {
+            return Ok(());
+        }
+        if limit == 0 {
+            return_errno_with_message!(Errno::EPERM, "locking memory is not allowed");
+        }
+
+        let locked_size = self.root_vmar().locked_size(0..MAX_USERSPACE_VADDR)
+            + self.pinned_size.load(Ordering::Relaxed);
+        if (locked_size + len) as u64 > limit {
+            return_errno_with_message!(Errno::ENOMEM, "the locked memory limit is exceeded");
+        }
+        Ok(())
     }
 
-    pub fn root_vmar(&self) -> &Vmar<Full> {
-        self.process_vm.root_vmar()
+    /// Returns the size of the memory that is pinned by the kernel in bytes.
+    pub fn pinned_size(&self) -> usize {
+        self.pinned_size.load(Ordering::Relaxed)
     }
 
-    pub fn heap(&self) -> &Heap {
-        self.process_vm.heap()
+    /// Accounts `len` bytes of memory that is going to be pinned by the kernel.
+    ///
+    /// The memory must be unaccounted by [`Self::unpin_memory`] after it is unpinned.
+    pub fn pin_memory(&self, len: usize) -> Result<()> {
+        self.check_locked_memory_limit(len)?;
+        self.pinned_size.fetch_add(len, Ordering::Relaxed);
+        Ok(())
     }
 
-    pub fn init_stack_reader(&self) -> InitStackReader {
-        self.process_vm.init_stack_reader()
+    /// Unaccounts `len` bytes of memory that is no longer pinned by the kernel.
+    pub fn unpin_memory(&self, len: usize) {
+        self.pinned_size.fetch_sub(len, Ordering::Relaxed);
     }
 
     // ****************** Signal ******************
@@ -613,27 +824,57 @@ impl Process {
     /// chooses an arbitrary thread to which to deliver the signal.
     ///
     /// TODO: restrict these method with access control tool.
-    pub fn enqueue_signal(&self, signal: impl Signal + Clone + 'static) {
+    pub fn enqueue_signal(&self, signal: impl Signal + 'static) {
         if self.status.is_zombie() {
             return;
         }
 
         // TODO: check that the signal is not user signal
 
-        // Enqueue signal to the first thread that does not block the signal
         let threads = self.tasks.lock();
-        for thread in threads.as_slice() {
-            let posix_thread = thread.as_posix_thread().unwrap();
-            if !posix_thread.has_signal_blocked(signal.num()) {
-                posix_thread.enqueue_signal(Box::new(signal));
-                return;
-            }
+        self.enqueue_shared_signal(Box::new(signal), &threads);
+    }
+
+    /// Enqueues a process-directed signal to the signal queue shared by the threads.
+    ///
+    /// The first thread that does not block the signal is woken up to handle it. If all threads
+    /// block the signal, the signal stays pending until a thread unblocks it or dequeues it via
+    /// a signalfd.
+    pub(in crate::process) fn enqueue_shared_signal(
+        &self,
+        signal: Box<dyn Signal>,
+        threads: &TaskSet,
+    ) {
+        let signum = signal.num();
+        self.sig_queues.enqueue(signal);
+
+        if let Some(posix_thread) = threads
+            .as_slice()
+            .iter()
+            .map(|thread| thread.as_posix_thread().unwrap())
+            .find(|posix_thread| !posix_thread.has_signal_blocked(signum))
+        {
+            posix_thread.wake_up_by_signal(signum);
         }
+    }
+
+    /// Returns the queue of the pending process-directed signals.
+    pub(in crate::process) fn sig_queues(&self) -> &SigQueues {
+        &self.sig_queues
+    }
 
-        // If all threads block the signal, enqueue signal to the main thread
-        let thread = threads.main();
-        let posix_thread = thread.as_posix_thread().unwrap();
-        posix_thread.enqueue_signal(Box::new(signal));
+    /// Registers an observer of the process-directed signals.
+    pub fn register_sigqueue_observer(
+        &self,
+        observer: Weak<dyn Observer<SigEvents>>,
+        filter: SigEventsFilter,
+    ) {
+        self.sig_queues.register_observer(observer, filter);
+    }
+
+    /// Unregisters an observer of the process-directed signals.
+    pub fn unregister_sigqueue_observer(&self, observer: &Weak<dyn Observer<SigEvents>>) {
+        self.sig_queues.unregister_observer(observer);
     }
 
     /// Clears the parent death signal.
@@ -662,6 +903,99 @@ impl Process {
         self.exit_signal.as_sig_num()
     }
 
+    /// Returns whether the process can be dumped into a core file.
+    pub fn is_dumpable(&self) -> bool {
+        self.is_dumpable.load(Ordering::Relaxed)
+    }
+
+    /// Sets whether the process can be dumped into a core file.
+    pub fn set_dumpable(&self, is_dumpable: bool) {
+        self.is_dumpable.store(is_dumpable, Ordering::Relaxed);
+    }
+
+    /// Returns whether the process has called `execve` since it was created by `fork`.
+    pub fn has_called_execve(&self) -> bool {
+        self.has_called_execve.load(Ordering::Relaxed)
+    }
+
+    /// Marks that the process has called `execve`.
+    pub fn set_called_execve(&self) {
+        self.has_called_execve.store(true, Ordering::Relaxed);
+    }
+
+    /// Returns the personality of the process.
+    ///
+    /// The flags in the personality are defined by [`Personality`].
+    ///
+    /// [`Personality`]: crate::process::personality::Personality
+    pub fn personality(&self) -> u32 {
+        self.personality.load(Ordering::Relaxed)
+    }
+
+    /// Sets the personality of the process.
+    pub fn set_personality(&self, personality: u32) {
+        self.personality.store(personality, Ordering::Relaxed);
+    }
+
+    /// Returns the adjustment to the badness score of the OOM killer.
+    ///
+    /// The value is in the range of [`OOM_SCORE_ADJ_MIN`] to [`OOM_SCORE_ADJ_MAX`].
+    ///
+    /// [`OOM_SCORE_ADJ_MIN`]: crate::process::oom::OOM_SCORE_ADJ_MIN
+    /// [`OOM_SCORE_ADJ_MAX`]: crate::process::oom::OOM_SCORE_ADJ_MAX
+    pub fn oom_score_adj(&self) -> i16 {
+        self.oom_score_adj.load(Ordering::Relaxed)
+    }
+
+    /// Sets the adjustment to the badness score of the OOM killer.
+    pub fn set_oom_score_adj(&self, oom_score_adj: i16) {
+        self.oom_score_adj.store(oom_score_adj, Ordering::Relaxed);
+    }
+
+    /// Stops all threads of the process because of the stop signal, and notifies the parent.
+    pub fn stop(&self, sig_num: SigNum) {
+        for task in self.tasks.lock().as_slice() {
+            let _ = task.as_thread().unwrap().stop();
+        }
+
+        self.status.set_stopped(sig_num);
+        self.notify_parent_job_status();
+    }
+
+    /// Resumes all threads of the process, and notifies the parent if the process was stopped.
+    pub fn resume(&self) {
+        for task in self.tasks.lock().as_slice() {
+            let _ = task.as_thread().unwrap().resume();
+        }
+
+        if self.status.set_continued() {
+            self.notify_parent_job_status();
+        }
+    }
+
+    fn notify_parent_job_status(&self) {
+        let Some(parent) = self.parent.lock().process().upgrade() else {
+            return;
+        };
+
+        // The parent does not receive `SIGCHLD` when its children stop or continue if the flag
+        // is set.
+        let sig_action = parent.sig_dispositions().lock().get(SIGCHLD);
+        let is_nocldstop = matches!(
+            sig_action,
+            SigAction::User { flags, .. } if flags.contains(SigActionFlags::SA_NOCLDSTOP)
+        );
+        if !is_nocldstop {
+            parent.enqueue_signal(KernelSignal::new(SIGCHLD));
+        }
+        parent.children_wait_queue().wake_all();
+    }
+
+    /// Returns the pollee that notifies the pidfds when the process exits.
+    pub fn pidfd_pollee(&self) -> &Pollee {
+        &self.pidfd_pollee
+    }
+
     // ******************* Status ********************
 
     /// Returns a reference to the process status.
@@ -670,6 +1004,13 @@ impl Process {
     }
 }
 
+impl Drop for Process {
+    fn drop(&mut self) {
+        // The IDs of the other threads are freed when the threads exit.
+        self.pid_ns.detach(self.pid);
+    }
+}
+
 #[cfg(ktest)]
 mod test {
 
@@ -688,11 +1029,13 @@ mod test {
         };
         Process::new(
             pid,
+            PidNamespace::get_init_singleton().clone(),
             parent,
             String::new(),
-            ProcessVm::alloc(),
+            Arc::new(ProcessVm::alloc()),
             ResourceLimits::default(),
             Nice::default(),
+            Cgroup::root().clone(),
             Arc::new(Mutex::new(SigDispositions::default())),
         )
     }
@@ -754,8 +1097,13 @@ mod test {
         crate::time::clocks::init_for_ktest();
         let process = new_process_in_session(None);
         let sess = process.session().unwrap();
-        sess.inner.lock().leader = None;
 
+        assert!(process.is_session_leader());
+        assert!(process
+            .to_new_session()
+            .is_err_and(|e| e.error() == Errno::EPERM));
+
+        sess.inner.lock().leader = None;
         assert!(!process.is_session_leader());
         assert!(process
             .to_new_session()
@@ -60,11 +60,6 @@ impl ProcessGroup {
         })
     }
 
-    /// Returns whether self contains a process with `pid`.
-    pub(in crate::process) fn contains_process(&self, pid: Pid) -> bool {
-        self.inner.lock().processes.contains_key(&pid)
-    }
-
     /// Returns the process group identifier
     pub fn pgid(&self) -> Pgid {
         self.pgid
@@ -88,6 +83,34 @@ impl ProcessGroup {
         }
     }
 
+    /// Returns whether the process group is orphaned.
+    ///
+    /// A process group is orphaned if the parent of every process in the group either belongs to
+    /// the group itself, or does not belong to the session of the group. In this case, no
+    /// process in the session could continue the group if it is stopped.
+    pub fn is_orphaned(&self) -> bool {
+        let Some(session) = self.session() else {
+            return true;
+        };
+
+        // Lock order: group of process -> group inner, so the processes are collected first.
+        let processes: Vec<_> = self.inner.lock().processes.values().cloned().collect();
+        processes.iter().all(|process| {
+            if process.status().is_zombie() {
+                return true;
+            }
+
+            let Some(parent) = process.parent().lock().process().upgrade() else {
+                return true;
+            };
+
+            parent.pgid() == self.pgid
+                || parent
+                    .session()
+                    .is_none_or(|parent_session| !Arc::ptr_eq(&parent_session, &session))
+        })
+    }
+
     /// Returns the leader process.
     pub fn leader(&self) -> Option<Arc<Process>> {
         self.inner.lock().leader.clone()
@@ -21,7 +21,10 @@ pub trait Terminal: FileIo {
 
     /// Sets the foreground process group of this terminal.
     ///
-    /// If the terminal is not controlling terminal, this method returns `ENOTTY`.
+    /// If the terminal is not controlling terminal, this method returns `ENOTTY`. If the
+    /// process group does not exist, this method returns `ESRCH`. Like other changes to the
+    /// terminal, a background process group may be stopped by `SIGTTOU` (see
+    /// [`JobControl::check_write`]).
     ///
     /// # Panics
     ///
@@ -31,9 +34,13 @@ pub trait Terminal: FileIo {
             return_errno_with_message!(Errno::ENOTTY, "self is not controlling terminal");
         }
 
-        let foreground = process_table::get_process_group(pgid);
+        self.job_control().check_write()?;
 
-        self.job_control().set_foreground(foreground.as_ref())
+        let Some(foreground) = process_table::get_process_group(pgid) else {
+            return_errno_with_message!(Errno::ESRCH, "the process group does not exist");
+        };
+
+        self.job_control().set_foreground(&foreground)
     }
 
     // *************** Session and controlling terminal ***************
@@ -1,24 +1,25 @@
 // SPDX-License-Identifier: MPL-2.0
 
-use alloc::{
-    boxed::Box,
-    sync::{Arc, Weak},
-    vec::Vec,
-};
 use core::time::Duration;
 
 use id_alloc::IdAlloc;
 use ostd::{
     arch::{timer::TIMER_FREQ, trap::is_kernel_interrupted},
-    sync::Mutex,
     timer,
 };
 
 use super::Process;
 use crate::{
+    prelude::*,
     process::{
         posix_thread::AsPosixThread,
-        signal::{constants::SIGALRM, signals::kernel::KernelSignal},
+        rlimit::{RLimit64, RLIM_INFINITY},
+        signal::{
+            constants::{SIGALRM, SIGKILL, SIGPROF, SIGVTALRM, SIGXCPU},
+            sig_num::SigNum,
+            signals::{kernel::KernelSignal, timer::TimerOverrun},
+        },
+        ResourceType,
     },
     thread::{
         work_queue::{submit_work_item, work_item::WorkItem},
@@ -26,7 +27,8 @@ use crate::{
     },
     time::{
         clocks::{ProfClock, RealTimeClock},
-        Timer, TimerManager,
+        timer::Timeout,
+        Clock, Timer, TimerManager,
     },
 };
 
@@ -89,17 +91,44 @@ pub struct PosixTimerManager {
     virtual_timer: Arc<Timer>,
     /// A timer based on the profiling clock.
     prof_timer: Arc<Timer>,
+    /// A timer based on the profiling clock that enforces `RLIMIT_CPU`.
+    cpu_limit_timer: Arc<Timer>,
     /// An ID allocator to allocate unique timer IDs.
     id_allocator: Mutex<IdAlloc>,
     /// A container managing all POSIX timers created by `timer_create()` syscall
     /// within the process context.
-    posix_timers: Mutex<Vec<Option<Arc<Timer>>>>,
+    posix_timers: Mutex<Vec<Option<Arc<PosixTimer>>>>,
+}
+
+/// A POSIX timer created by `timer_create()`.
+pub struct PosixTimer {
+    timer: Arc<Timer>,
+    overrun: Arc<TimerOverrun>,
+}
+
+impl PosixTimer {
+    pub fn new(timer: Arc<Timer>, overrun: Arc<TimerOverrun>) -> Self {
+        Self { timer, overrun }
+    }
+
+    /// Returns the underlying timer.
+    pub fn timer(&self) -> &Arc<Timer> {
+        &self.timer
+    }
+
+    /// Returns the overrun count of the signals sent by the timer.
+    pub fn overrun(&self) -> &TimerOverrun {
+        &self.overrun
+    }
 }
 
-fn create_process_timer_callback(process_ref: &Weak<Process>) -> impl Fn() + Clone {
+fn create_process_timer_callback(
+    process_ref: &Weak<Process>,
+    sig_num: SigNum,
+) -> impl Fn() + Clone {
     let current_process = process_ref.clone();
     let sent_signal = move || {
-        let signal = KernelSignal::new(SIGALRM);
+        let signal = KernelSignal::new(sig_num);
         if let Some(process) = current_process.upgrade() {
             process.enqueue_signal(signal);
         }
@@ -116,22 +145,75 @@ fn create_process_timer_callback(process_ref: &Weak<Process>) -> impl Fn() + Clo
     }
 }
 
+fn create_cpu_limit_timer_callback(process_ref: &Weak<Process>) -> impl Fn() + Clone {
+    let current_process = process_ref.clone();
+    let check_limit = move || {
+        if let Some(process) = current_process.upgrade() {
+            enforce_cpu_limit(&process);
+        }
+    };
+
+    let work_func = Box::new(check_limit);
+    let work_item = WorkItem::new(work_func);
+
+    move || {
+        submit_work_item(
+            work_item.clone(),
+            crate::thread::work_queue::WorkPriority::High,
+        );
+    }
+}
+
+/// Sends `SIGXCPU` or `SIGKILL` to the process if its CPU time reaches `RLIMIT_CPU`.
+///
+/// Like Linux, when the soft limit is reached, `SIGXCPU` is sent and the soft limit is raised by
+/// one second, so `SIGXCPU` is sent every second until the hard limit is reached, at which point
+/// the process is killed by `SIGKILL`.
+fn enforce_cpu_limit(process: &Process) {
+    let cpu_time = process.prof_clock().read_time();
+
+    let mut resource_limits = process.resource_limits().lock();
+    let rlimit = resource_limits.get_rlimit_mut(ResourceType::RLIMIT_CPU);
+    let (soft_limit, hard_limit) = (rlimit.get_cur(), rlimit.get_max());
+
+    if cpu_time >= Duration::from_secs(hard_limit) {
+        process.enqueue_signal(KernelSignal::new(SIGKILL));
+        return;
+    }
+
+    if cpu_time >= Duration::from_secs(soft_limit) {
+        process.enqueue_signal(KernelSignal::new(SIGXCPU));
+        if soft_limit < hard_limit {
+            *rlimit = RLimit64::new(soft_limit + 1, hard_limit);
+        }
+    }
+
+    process.timer_manager().set_cpu_limit(rlimit);
+}
+
 impl PosixTimerManager {
     pub(super) fn new(prof_clock: &Arc<ProfClock>, process_ref: &Weak<Process>) -> Self {
         const MAX_NUM_OF_POSIX_TIMERS: usize = 10000;
 
-        let callback = create_process_timer_callback(process_ref);
+        let alarm_timer = RealTimeClock::timer_manager()
+            .create_timer(create_process_timer_callback(process_ref, SIGALRM));
 
-        let alarm_timer = RealTimeClock::timer_manager().create_timer(callback.clone());
+        // Like Linux, the expirations of `ITIMER_VIRTUAL` and `ITIMER_PROF` are notified by
+        // `SIGVTALRM` and `SIGPROF`, respectively.
+        let virtual_timer = TimerManager::new(prof_clock.user_clock().clone())
+            .create_timer(create_process_timer_callback(process_ref, SIGVTALRM));
+        let prof_timer = TimerManager::new(prof_clock.clone())
+            .create_timer(create_process_timer_callback(process_ref, SIGPROF));
 
-        let virtual_timer =
-            TimerManager::new(prof_clock.user_clock().clone()).create_timer(callback.clone());
-        let prof_timer = TimerManager::new(prof_clock.clone()).create_timer(callback);
+        let cpu_limit_timer = prof_timer
+            .timer_manager()
+            .create_timer(create_cpu_limit_timer_callback(process_ref));
 
         Self {
             alarm_timer,
             virtual_timer,
             prof_timer,
+            cpu_limit_timer,
             id_allocator: Mutex::new(IdAlloc::with_capacity(MAX_NUM_OF_POSIX_TIMERS)),
             posix_timers: Mutex::new(Vec::new()),
         }
@@ -152,6 +234,20 @@ impl PosixTimerManager {
         &self.prof_timer
     }
 
+    /// Arms the timer that enforces `RLIMIT_CPU` according to the new limit.
+    ///
+    /// The timer expires when the CPU time of the process reaches the soft limit or the hard
+    /// limit, whichever is smaller.
+    pub fn set_cpu_limit(&self, rlimit: &RLimit64) {
+        let limit = rlimit.get_cur().min(rlimit.get_max());
+        if limit == RLIM_INFINITY {
+            self.cpu_limit_timer.cancel();
+        } else {
+            self.cpu_limit_timer
+                .set_timeout(Timeout::When(Duration::from_secs(limit)));
+        }
+    }
+
     /// Creates a timer based on the profiling CPU clock of the current process.
     pub fn create_prof_timer<F>(&self, func: F) -> Arc<Timer>
     where
@@ -170,21 +266,37 @@ impl PosixTimerManager {
 
     /// Adds a POSIX timer to the managed `posix_timers`, and allocate a timer ID for this timer.
     /// Return the timer ID.
-    pub fn add_posix_timer(&self, posix_timer: Arc<Timer>) -> usize {
+    ///
+    /// The timer is created by `create_timer` with the allocated timer ID, since the signals
+    /// sent by the timer carry the ID.
+    pub fn add_posix_timer<F>(&self, create_timer: F) -> Result<usize>
+    where
+        F: FnOnce(usize) -> Result<PosixTimer>,
+    {
         let mut timers = self.posix_timers.lock();
         // Holding the lock of `posix_timers` is required to operate the `id_allocator`.
-        let timer_id = self.id_allocator.lock().alloc().unwrap();
+        let Some(timer_id) = self.id_allocator.lock().alloc() else {
+            return_errno_with_message!(Errno::EAGAIN, "too many POSIX timers");
+        };
+        let posix_timer = match create_timer(timer_id) {
+            Ok(posix_timer) => posix_timer,
+            Err(err) => {
+                self.id_allocator.lock().free(timer_id);
+                return Err(err);
+            }
+        };
+
         if timers.len() < timer_id + 1 {
             timers.resize(timer_id + 1, None);
         }
         // The ID allocated is not used by any other timers so this index in `timers`
         // must be `None`.
-        timers[timer_id] = Some(posix_timer);
-        timer_id
+        timers[timer_id] = Some(Arc::new(posix_timer));
+        Ok(timer_id)
     }
 
     /// Finds a POSIX timer by the input `timer_id`.
-    pub fn find_posix_timer(&self, timer_id: usize) -> Option<Arc<Timer>> {
+    pub fn find_posix_timer(&self, timer_id: usize) -> Option<Arc<PosixTimer>> {
         let timers = self.posix_timers.lock();
         if timer_id >= timers.len() {
             return None;
@@ -194,7 +306,7 @@ impl PosixTimerManager {
     }
 
     /// Removes the POSIX timer with the ID `timer_id`.
-    pub fn remove_posix_timer(&self, timer_id: usize) -> Option<Arc<Timer>> {
+    pub fn remove_posix_timer(&self, timer_id: usize) -> Option<Arc<PosixTimer>> {
         let mut timers = self.posix_timers.lock();
         if timer_id >= timers.len() {
             return None;
@@ -207,4 +319,20 @@ impl PosixTimerManager {
         }
         timer
     }
+
+    /// Removes and cancels all the POSIX timers.
+    ///
+    /// Like Linux, the POSIX timers are deleted when the process executes a new program or
+    /// exits.
+    pub fn clear_posix_timers(&self) {
+        let mut timers = self.posix_timers.lock();
+        let mut id_allocator = self.id_allocator.lock();
+        for (timer_id, timer) in timers.iter_mut().enumerate() {
+            if let Some(timer) = timer.take() {
+                timer.timer().cancel();
+                id_allocator.free(timer_id);
+            }
+        }
+        timers.clear();
+    }
 }
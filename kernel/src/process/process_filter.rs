@@ -13,15 +13,21 @@ pub enum ProcessFilter {
 }
 
 impl ProcessFilter {
+    // The IDs from the user space are the ones in the PID namespace of the current process, so
+    // they are translated to the global IDs. If no process has the ID in the namespace, the ID is
+    // translated to zero, which matches no processes.
+
     // used for waitid
+    //
+    // `P_PIDFD` (which = 3) does not refer to an ID, so it is handled by the caller.
     pub fn from_which_and_id(which: u64, id: u64) -> Result<Self> {
-        // Does not support PID_FD now(which = 3)
         // https://elixir.bootlin.com/linux/latest/source/include/uapi/linux/wait.h#L20
         match which {
             0 => Ok(ProcessFilter::Any),
-            1 => Ok(ProcessFilter::WithPid(id as Pid)),
-            2 => Ok(ProcessFilter::WithPgid(id as Pgid)),
-            3 => todo!(),
+            1 => Ok(ProcessFilter::WithPid(to_global_id(id as Pid))),
+            // Like Linux, zero means the process group of the current process.
+            2 if id == 0 => Ok(ProcessFilter::WithPgid(current!().pgid())),
+            2 => Ok(ProcessFilter::WithPgid(to_global_id(id as Pgid))),
             _ => return_errno_with_message!(Errno::EINVAL, "invalid which"),
         }
     }
@@ -32,7 +38,7 @@ impl ProcessFilter {
         // https://man7.org/linux/man-pages/man2/kill.2.html
         if wait_pid < -1 {
             // process group ID is equal to the absolute value of pid.
-            ProcessFilter::WithPgid((-wait_pid) as Pgid)
+            ProcessFilter::WithPgid(to_global_id((-wait_pid) as Pgid))
         } else if wait_pid == -1 {
             // wait for any child process
             ProcessFilter::Any
@@ -42,7 +48,7 @@ impl ProcessFilter {
             ProcessFilter::WithPgid(pgid)
         } else {
             // pid > 0. wait for the child whose process ID is equal to the value of pid.
-            ProcessFilter::WithPid(wait_pid as Pid)
+            ProcessFilter::WithPid(to_global_id(wait_pid as Pid))
         }
     }
 
@@ -54,3 +60,7 @@ impl ProcessFilter {
         }
     }
 }
+
+fn to_global_id(id: Pid) -> Pid {
+    current!().pid_ns().global_id(id).unwrap_or(0)
+}
@@ -0,0 +1,105 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! User address space layout randomization (ASLR).
+//!
+//! Like Linux, the randomization is controlled by `/proc/sys/kernel/randomize_va_space`:
+//! - `0`: No randomization.
+//! - `1`: The bases of the stack and the mappings (including the dynamic loader, the
+//!   position-independent executables and the vDSO) are randomized.
+//! - `2`: The base of the heap is also randomized. This is the default.
+//!
+//! A process can opt out of the randomization of its next programs by setting
+//! [`Personality::ADDR_NO_RANDOMIZE`], e.g., with `setarch -R`.
+
+use core::sync::atomic::{AtomicU8, Ordering};
+
+use crate::{prelude::*, process::personality::Personality, util::random::getrandom};
+
+/// The maximum value of `randomize_va_space`.
+pub const MAX_RANDOMIZE_VA_SPACE: u8 = 2;
+
+/// The number of random bits of the stack base in pages, which is the same as Linux on x86-64.
+const STACK_RANDOM_BITS: u32 = 22;
+/// The number of random bits of the mapping base in pages, which is the same as Linux on x86-64.
+const MMAP_RANDOM_BITS: u32 = 28;
+/// The range of the heap base, which is the same as Linux.
+const BRK_RANDOM_RANGE: usize = 0x200_0000;
+/// The number of random pages of the stack base for the 32-bit programs, which is the same as
+/// Linux.
+#[cfg(target_arch = "x86_64")]
+const COMPAT_STACK_RANDOM_PAGES: usize = 0x7ff;
+/// The number of random bits of the mapping base in pages for the 32-bit programs, which is the
+/// same as Linux.
+#[cfg(target_arch = "x86_64")]
+const COMPAT_MMAP_RANDOM_BITS: u32 = 8;
+
+static RANDOMIZE_VA_SPACE: AtomicU8 = AtomicU8::new(MAX_RANDOMIZE_VA_SPACE);
+
+/// Returns the current value of `randomize_va_space`.
+pub fn randomize_va_space() -> u8 {
+    RANDOMIZE_VA_SPACE.load(Ordering::Relaxed)
+}
+
+/// Sets the value of `randomize_va_space`.
+pub fn set_randomize_va_space(value: u8) -> Result<()> {
+    if value > MAX_RANDOMIZE_VA_SPACE {
+        return_errno_with_message!(Errno::EINVAL, "the value is invalid");
+    }
+    RANDOMIZE_VA_SPACE.store(value, Ordering::Relaxed);
+    Ok(())
+}
+
+/// The random offsets of the address space of a program.
+///
+/// All the offsets are multiples of [`PAGE_SIZE`]. They are zero if the randomization is
+/// disabled.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Aslr {
+    /// The offset below the highest address of the stack.
+    pub stack_offset: usize,
+    /// The offset above the end of the space reserved for the heap, where the mappings start.
+    pub mmap_offset: usize,
+    /// The offset above the base address of the heap.
+    pub brk_offset: usize,
+}
+
+impl Aslr {
+    /// Chooses the random offsets for a program that runs with the personality.
+    pub fn new(personality: Personality) -> Self {
+        let level = randomize_va_space();
+        if level == 0 || personality.contains(Personality::ADDR_NO_RANDOMIZE) {
+            return Self::default();
+        }
+
+        let stack_offset = random_pages(1 << STACK_RANDOM_BITS);
+        let mmap_offset = random_pages(1 << MMAP_RANDOM_BITS);
+        let brk_offset = if level >= 2 {
+            random_pages(BRK_RANDOM_RANGE / PAGE_SIZE)
+        } else {
+            0
+        };
+
+        Self {
+            stack_offset,
+            mmap_offset,
+            brk_offset,
+        }
+    }
+
+    /// Narrows the random offsets to fit in the address space of a 32-bit program.
+    #[cfg(target_arch = "x86_64")]
+    pub fn compat(self) -> Self {
+        Self {
+            stack_offset: self.stack_offset % (COMPAT_STACK_RANDOM_PAGES * PAGE_SIZE),
+            mmap_offset: self.mmap_offset % ((1 << COMPAT_MMAP_RANDOM_BITS) * PAGE_SIZE),
+            brk_offset: self.brk_offset,
+        }
+    }
+}
+
+/// Returns a random number of pages in `[0, nr_pages)`, in bytes.
+fn random_pages(nr_pages: usize) -> usize {
+    let mut value: usize = 0;
+    getrandom(value.as_bytes_mut());
+    (value % nr_pages) * PAGE_SIZE
+}
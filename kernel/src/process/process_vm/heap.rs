@@ -1,12 +1,16 @@
 // SPDX-License-Identifier: MPL-2.0
 
-use core::sync::atomic::{AtomicUsize, Ordering};
+use core::{
+    ops::Range,
+    sync::atomic::{AtomicUsize, Ordering},
+};
 
 use align_ext::AlignExt;
 use aster_rights::Full;
 
 use crate::{
     prelude::*,
+    process::ResourceType,
     vm::{perms::VmPerms, vmar::Vmar},
 };
 
@@ -27,10 +31,15 @@ pub struct Heap {
 
 impl Heap {
     pub const fn new() -> Self {
+        Self::new_at(USER_HEAP_BASE)
+    }
+
+    /// Creates a heap whose lowest address is `base`.
+    pub const fn new_at(base: Vaddr) -> Self {
         Heap {
-            base: USER_HEAP_BASE,
+            base,
             limit: USER_HEAP_SIZE_LIMIT,
-            current_heap_end: AtomicUsize::new(USER_HEAP_BASE),
+            current_heap_end: AtomicUsize::new(base),
         }
     }
 
@@ -65,15 +74,11 @@ impl Heap {
         Ok(())
     }
 
-    pub fn brk(&self, new_heap_end: Option<Vaddr>) -> Result<Vaddr> {
-        let current = current!();
-        let root_vmar = current.root_vmar();
+    pub fn brk(&self, new_heap_end: Option<Vaddr>, ctx: &Context) -> Result<Vaddr> {
+        let root_vmar = ctx.thread_local.root_vmar();
         match new_heap_end {
             None => Ok(self.current_heap_end.load(Ordering::Relaxed)),
             Some(new_heap_end) => {
-                if new_heap_end > self.base + self.limit {
-                    return_errno_with_message!(Errno::ENOMEM, "heap size limit was met.");
-                }
                 let current_heap_end = self.current_heap_end.load(Ordering::Acquire);
 
                 if new_heap_end <= current_heap_end {
@@ -81,13 +86,34 @@ impl Heap {
                     return Ok(current_heap_end);
                 }
 
-                let current_heap_end = current_heap_end.align_up(PAGE_SIZE);
+                // Like Linux, if the heap cannot grow due to the limits, the current program
+                // break is returned instead of an error.
+                let data_limit = ctx
+                    .process
+                    .resource_limits()
+                    .lock()
+                    .get_rlimit(ResourceType::RLIMIT_DATA)
+                    .get_cur();
+                if new_heap_end > self.base + self.limit
+                    || (new_heap_end - self.base) as u64 > data_limit
+                {
+                    return Ok(current_heap_end);
+                }
+
+                let old_heap_end = current_heap_end.align_up(PAGE_SIZE);
                 let new_heap_end = new_heap_end.align_up(PAGE_SIZE);
+                if ctx
+                    .process
+                    .check_address_space_limit(new_heap_end - old_heap_end, None)
+                    .is_err()
+                {
+                    return Ok(current_heap_end);
+                }
 
                 // Remove the reserved space.
-                root_vmar.remove_mapping(current_heap_end..new_heap_end)?;
+                root_vmar.remove_mapping(old_heap_end..new_heap_end)?;
 
-                let old_size = current_heap_end - self.base;
+                let old_size = old_heap_end - self.base;
                 let new_size = new_heap_end - self.base;
 
                 // Expand the heap.
@@ -99,6 +125,12 @@ impl Heap {
         }
     }
 
+    /// Returns the range that is reserved for the heap to grow into.
+    pub(super) fn reserved_range(&self) -> Range<Vaddr> {
+        let current_heap_end = self.current_heap_end.load(Ordering::Relaxed);
+        current_heap_end.align_up(PAGE_SIZE)..self.base + self.limit
+    }
+
     pub(super) fn set_uninitialized(&self) {
         self.current_heap_end
             .store(self.base + PAGE_SIZE, Ordering::Relaxed);
@@ -15,15 +15,16 @@
 
 use core::{
     mem,
-    sync::atomic::{AtomicUsize, Ordering},
+    sync::atomic::{AtomicBool, AtomicUsize, Ordering},
 };
 
 use align_ext::AlignExt;
 use aster_rights::Full;
-use ostd::mm::{vm_space::VmItem, UntypedMem, VmIo, VmSpace, MAX_USERSPACE_VADDR};
+use ostd::mm::{vm_space::VmItem, Infallible, UntypedMem, VmIo, VmSpace, MAX_USERSPACE_VADDR};
 
 use self::aux_vec::{AuxKey, AuxVec};
 use crate::{
+    arch::cpu::{COMPAT_ELF_PLATFORM, ELF_PLATFORM},
     prelude::*,
     util::random::getrandom,
     vm::{
@@ -94,11 +95,17 @@ pub const MAX_ENV_LEN: usize = 128;
  *  (low address)
  */
 
+/// The number of pages between the stack top and the highest address.
+///
+/// We do not want the stack top too close to the highest address. So we add this fixed padding.
+/// Any small value greater than zero will do.
+const NR_FIXED_PADDING_PAGES: usize = 7;
+
 /// The initial portion of the main stack of a process.
 pub struct InitStack {
     /// The initial highest address.
     /// The stack grows down from this address
-    initial_top: Vaddr,
+    initial_top: AtomicUsize,
     /// The max allowed stack size
     max_size: usize,
     /// The current stack pointer.
@@ -106,43 +113,52 @@ pub struct InitStack {
     /// After initialized, `pos` points to the user stack pointer(rsp)
     /// of the process.
     pos: Arc<AtomicUsize>,
+    /// Whether the stack is laid out for a 32-bit program, whose pointers are 32 bits.
+    is_compat: AtomicBool,
 }
 
 impl Clone for InitStack {
     fn clone(&self) -> Self {
         Self {
-            initial_top: self.initial_top,
+            initial_top: AtomicUsize::new(self.initial_top()),
             max_size: self.max_size,
             pos: Arc::new(AtomicUsize::new(self.pos.load(Ordering::Relaxed))),
+            is_compat: AtomicBool::new(self.is_compat()),
         }
     }
 }
 
 impl InitStack {
-    pub(super) fn new() -> Self {
-        let nr_pages_padding = {
-            // We do not want the stack top too close to MAX_USERSPACE_VADDR.
-            // So we add this fixed padding. Any small value greater than zero will do.
-            const NR_FIXED_PADDING_PAGES: usize = 7;
-
-            // Some random padding pages are added as a simple measure to
-            // make the stack values of a buggy user program harder
-            // to be exploited by attackers.
-            let mut nr_random_padding_pages: u8 = 0;
-            getrandom(nr_random_padding_pages.as_bytes_mut()).unwrap();
-
-            nr_random_padding_pages as usize + NR_FIXED_PADDING_PAGES
-        };
-        let initial_top = MAX_USERSPACE_VADDR - PAGE_SIZE * nr_pages_padding;
+    /// Creates an init stack whose top is `random_offset` bytes lower than the
+    /// highest possible address.
+    ///
+    /// The random offset makes the stack values of a buggy user program harder
+    /// to be exploited by attackers.
+    pub(super) fn new(random_offset: usize) -> Self {
+        let initial_top = MAX_USERSPACE_VADDR - PAGE_SIZE * NR_FIXED_PADDING_PAGES - random_offset;
         let max_size = INIT_STACK_SIZE;
 
         Self {
-            initial_top,
+            initial_top: AtomicUsize::new(initial_top),
             max_size,
             pos: Arc::new(AtomicUsize::new(initial_top)),
+            is_compat: AtomicBool::new(false),
         }
     }
 
+    /// Lays out the stack for a 32-bit program, whose top is `random_offset` bytes lower than
+    /// `max_vaddr`.
+    ///
+    /// This method must be called before the stack is mapped.
+    pub(super) fn set_compat(&self, max_vaddr: Vaddr, random_offset: usize) {
+        debug_assert!(!self.is_initialized());
+
+        let initial_top = max_vaddr - PAGE_SIZE * NR_FIXED_PADDING_PAGES - random_offset;
+        self.initial_top.store(initial_top, Ordering::Relaxed);
+        self.pos.store(initial_top, Ordering::Relaxed);
+        self.is_compat.store(true, Ordering::Relaxed);
+    }
+
     /// Returns the user stack top(highest address), used to setup rsp.
     ///
     /// This method should only be called after the stack is initialized.
@@ -153,13 +169,16 @@ impl InitStack {
         stack_top
     }
 
-    /// Maps the VMO of the init stack and constructs a writer to initialize its content.
+    /// Maps the VMO of the init stack with `perms` and constructs a writer to initialize its
+    /// content.
     pub(super) fn map_and_write(
         &self,
         root_vmar: &Vmar<Full>,
         argv: Vec<CString>,
         envp: Vec<CString>,
+        execfn: CString,
         auxvec: AuxVec,
+        perms: VmPerms,
     ) -> Result<()> {
         self.set_uninitialized();
 
@@ -168,8 +187,7 @@ impl InitStack {
             vmo_options.alloc()?
         };
         let vmar_map_options = {
-            let perms = VmPerms::READ | VmPerms::WRITE;
-            let map_addr = self.initial_top - self.max_size;
+            let map_addr = self.initial_top() - self.max_size;
             debug_assert!(map_addr % PAGE_SIZE == 0);
             root_vmar
                 .new_map(self.max_size, perms)?
@@ -183,8 +201,10 @@ impl InitStack {
             vmo,
             argv,
             envp,
+            execfn,
             auxvec,
-            map_addr: self.initial_top - self.max_size,
+            map_addr: self.initial_top() - self.max_size,
+            is_compat: self.is_compat(),
         };
         writer.write()
     }
@@ -196,16 +216,25 @@ impl InitStack {
         InitStackReader {
             base: self.pos(),
             vm_space,
-            map_addr: self.initial_top - self.max_size,
+            map_addr: self.initial_top() - self.max_size,
+            is_compat: self.is_compat(),
         }
     }
 
     fn is_initialized(&self) -> bool {
-        self.pos() != self.initial_top
+        self.pos() != self.initial_top()
     }
 
     fn set_uninitialized(&self) {
-        self.pos.store(self.initial_top, Ordering::Relaxed);
+        self.pos.store(self.initial_top(), Ordering::Relaxed);
+    }
+
+    fn initial_top(&self) -> Vaddr {
+        self.initial_top.load(Ordering::Relaxed)
+    }
+
+    fn is_compat(&self) -> bool {
+        self.is_compat.load(Ordering::Relaxed)
     }
 
     fn pos(&self) -> Vaddr {
@@ -219,9 +248,13 @@ struct InitStackWriter {
     vmo: Vmo<Full>,
     argv: Vec<CString>,
     envp: Vec<CString>,
+    /// The path of the executable file, which is referenced by `AT_EXECFN`.
+    execfn: CString,
     auxvec: AuxVec,
     /// The mapping address of the `InitStack`.
     map_addr: usize,
+    /// Whether the words are written in 32 bits for a 32-bit program.
+    is_compat: bool,
 }
 
 impl InitStackWriter {
@@ -231,10 +264,23 @@ impl InitStackWriter {
 
         let argc = self.argv.len() as u64;
 
+        // Write the path of the executable file at the highest address, like Linux
+        let execfn_pointer = self.write_cstring(&self.execfn)?;
+        self.auxvec.set(AuxKey::AT_EXECFN, execfn_pointer)?;
         // Write envp string
         let envp_pointers = self.write_envp_strings()?;
         // Write argv string
         let argv_pointers = self.write_argv_strings()?;
+        // Write the platform string for auxvec
+        let platform = if self.is_compat {
+            COMPAT_ELF_PLATFORM
+        } else {
+            ELF_PLATFORM
+        };
+        if let Some(platform) = platform {
+            let platform_pointer = self.write_bytes(platform)?;
+            self.auxvec.set(AuxKey::AT_PLATFORM, platform_pointer)?;
+        }
         // Generate random values for auxvec
         let random_value_pointer = {
             let random_value = generate_random_for_aux_vec();
@@ -248,7 +294,7 @@ impl InitStackWriter {
         self.write_argv_pointers(argv_pointers)?;
 
         // write argc
-        self.write_u64(argc)?;
+        self.write_word(argc)?;
 
         // Ensure stack top is 16-bytes aligned
         debug_assert_eq!(self.pos() & !0xf, self.pos());
@@ -277,26 +323,27 @@ impl InitStackWriter {
     }
 
     /// Libc ABI requires 16-byte alignment of the stack entrypoint.
-    /// Current position of the stack is 8-byte aligned already, insert 8 byte
+    /// Current position of the stack is word-aligned already, insert words
     /// to meet the requirement if necessary.
     fn adjust_stack_alignment(&self, envp_pointers: &[u64], argv_pointers: &[u64]) -> Result<()> {
-        // Ensure 8-byte alignment
-        self.write_u64(0)?;
-        let auxvec_size = (self.auxvec.table().len() + 1) * (mem::size_of::<u64>() * 2);
-        let envp_pointers_size = (envp_pointers.len() + 1) * mem::size_of::<u64>();
-        let argv_pointers_size = (argv_pointers.len() + 1) * mem::size_of::<u64>();
-        let argc_size = mem::size_of::<u64>();
+        // Ensure word alignment
+        self.write_word(0)?;
+        let word_size = self.word_size();
+        let auxvec_size = (self.auxvec.table().len() + 1) * (word_size * 2);
+        let envp_pointers_size = (envp_pointers.len() + 1) * word_size;
+        let argv_pointers_size = (argv_pointers.len() + 1) * word_size;
+        let argc_size = word_size;
         let to_write_size = auxvec_size + envp_pointers_size + argv_pointers_size + argc_size;
-        if (self.pos() - to_write_size) % 16 != 0 {
-            self.write_u64(0)?;
+        while (self.pos() - to_write_size) % 16 != 0 {
+            self.write_word(0)?;
         }
         Ok(())
     }
 
     fn write_aux_vec(&self) -> Result<()> {
         // Write NULL auxiliary
-        self.write_u64(0)?;
-        self.write_u64(AuxKey::AT_NULL as u64)?;
+        self.write_word(0)?;
+        self.write_word(AuxKey::AT_NULL as u64)?;
         // Write Auxiliary vectors
         let aux_vec: Vec<_> = self
             .auxvec
@@ -305,43 +352,57 @@ impl InitStackWriter {
             .map(|(aux_key, aux_value)| (*aux_key, *aux_value))
             .collect();
         for (aux_key, aux_value) in aux_vec.iter() {
-            self.write_u64(*aux_value)?;
-            self.write_u64(*aux_key as u64)?;
+            self.write_word(*aux_value)?;
+            self.write_word(*aux_key as u64)?;
         }
         Ok(())
     }
 
     fn write_envp_pointers(&self, mut envp_pointers: Vec<u64>) -> Result<()> {
         // write NULL pointer
-        self.write_u64(0)?;
+        self.write_word(0)?;
         // write envp pointers
         envp_pointers.reverse();
         for envp_pointer in envp_pointers {
-            self.write_u64(envp_pointer)?;
+            self.write_word(envp_pointer)?;
         }
         Ok(())
     }
 
     fn write_argv_pointers(&self, mut argv_pointers: Vec<u64>) -> Result<()> {
         // write 0
-        self.write_u64(0)?;
+        self.write_word(0)?;
         // write argv pointers
         argv_pointers.reverse();
         for argv_pointer in argv_pointers {
-            self.write_u64(argv_pointer)?;
+            self.write_word(argv_pointer)?;
         }
         Ok(())
     }
 
-    /// Writes u64 to the stack.
+    /// Writes a word to the stack, which is truncated to u32 for a 32-bit program.
     /// Returns the writing address
-    fn write_u64(&self, val: u64) -> Result<u64> {
-        let start_address = (self.pos() - 8).align_down(8);
+    fn write_word(&self, val: u64) -> Result<u64> {
+        let word_size = self.word_size();
+        let start_address = (self.pos() - word_size).align_down(word_size);
         self.pos.store(start_address, Ordering::Relaxed);
-        self.vmo.write_val(start_address - self.map_addr, &val)?;
+        if self.is_compat {
+            self.vmo
+                .write_val(start_address - self.map_addr, &(val as u32))?;
+        } else {
+            self.vmo.write_val(start_address - self.map_addr, &val)?;
+        }
         Ok(self.pos() as u64)
     }
 
+    fn word_size(&self) -> usize {
+        if self.is_compat {
+            mem::size_of::<u32>()
+        } else {
+            mem::size_of::<u64>()
+        }
+    }
+
     /// Writes a CString including the ending null byte to the stack.
     /// Returns the writing address
     fn write_cstring(&self, val: &CString) -> Result<u64> {
@@ -366,7 +427,7 @@ impl InitStackWriter {
 
 fn generate_random_for_aux_vec() -> [u8; 16] {
     let mut rand_val = [0; 16];
-    getrandom(&mut rand_val).unwrap();
+    getrandom(&mut rand_val);
     rand_val
 }
 
@@ -376,6 +437,8 @@ pub struct InitStackReader<'a> {
     vm_space: &'a Arc<VmSpace>,
     /// The mapping address of the `InitStack`.
     map_addr: usize,
+    /// Whether the words are read in 32 bits for a 32-bit program.
+    is_compat: bool,
 }
 
 impl InitStackReader<'_> {
@@ -391,7 +454,7 @@ impl InitStackReader<'_> {
             return_errno_with_message!(Errno::EACCES, "Page not accessible");
         };
 
-        let argc = frame.read_val::<u64>(stack_base - page_base_addr)?;
+        let argc = self.read_word(&mut frame.reader().skip(stack_base - page_base_addr))? as u64;
         if argc > MAX_ARGV_NUMBER as u64 {
             return_errno_with_message!(Errno::EINVAL, "argc is corrupted");
         }
@@ -404,7 +467,7 @@ impl InitStackReader<'_> {
         let argc = self.argc()? as usize;
         // The reading offset in the initial stack is:
         // the initial stack bottom address + the size of `argc` in memory
-        let read_offset = self.init_stack_bottom() + size_of::<usize>();
+        let read_offset = self.init_stack_bottom() + self.word_size();
 
         let mut argv = Vec::with_capacity(argc);
         let page_base_addr = read_offset.align_down(PAGE_SIZE);
@@ -418,7 +481,7 @@ impl InitStackReader<'_> {
         let mut arg_ptr_reader = frame.reader().skip(read_offset - page_base_addr);
         for _ in 0..argc {
             let arg = {
-                let arg_ptr = arg_ptr_reader.read_val::<Vaddr>()?;
+                let arg_ptr = self.read_word(&mut arg_ptr_reader)?;
                 let arg_offset = arg_ptr
                     .checked_sub(page_base_addr)
                     .ok_or_else(|| Error::with_message(Errno::EINVAL, "arg_ptr is corrupted"))?;
@@ -440,13 +503,13 @@ impl InitStackReader<'_> {
         let argc = self.argc()? as usize;
         // The reading offset in the initial stack is:
         // the initial stack bottom address
-        // + the size of argc(8)
-        // + the size of arg pointer(8) * the number of arg(argc)
-        // + the size of null pointer(8)
+        // + the size of argc(a word)
+        // + the size of arg pointer(a word) * the number of arg(argc)
+        // + the size of null pointer(a word)
         let read_offset = self.init_stack_bottom()
-            + size_of::<usize>()
-            + size_of::<usize>() * argc
-            + size_of::<usize>();
+            + self.word_size()
+            + self.word_size() * argc
+            + self.word_size();
 
         let mut envp = Vec::new();
         let page_base_addr = read_offset.align_down(PAGE_SIZE);
@@ -460,7 +523,7 @@ impl InitStackReader<'_> {
         let mut envp_ptr_reader = frame.reader().skip(read_offset - page_base_addr);
         for _ in 0..MAX_ENVP_NUMBER {
             let env = {
-                let envp_ptr = envp_ptr_reader.read_val::<Vaddr>()?;
+                let envp_ptr = self.read_word(&mut envp_ptr_reader)?;
 
                 if envp_ptr == 0 {
                     break;
@@ -482,8 +545,66 @@ impl InitStackReader<'_> {
         Ok(envp)
     }
 
+    /// Reads the raw auxiliary vector from the process, including the terminating `AT_NULL` entry.
+    pub fn auxv(&self) -> Result<Vec<u8>> {
+        let argc = self.argc()? as usize;
+        // The `envp` pointers start at the same offset as in `Self::envp`.
+        let read_offset = self.init_stack_bottom()
+            + self.word_size()
+            + self.word_size() * argc
+            + self.word_size();
+
+        let page_base_addr = read_offset.align_down(PAGE_SIZE);
+        let mut cursor = self
+            .vm_space
+            .cursor(&(page_base_addr..page_base_addr + PAGE_SIZE))?;
+        let VmItem::Mapped { frame, .. } = cursor.query()? else {
+            return_errno_with_message!(Errno::EACCES, "Page not accessible");
+        };
+
+        let mut reader = frame.reader().skip(read_offset - page_base_addr);
+        // Skip the `envp` pointers and the null pointer after them.
+        while self.read_word(&mut reader)? != 0 {}
+
+        // Like Linux, the entries are of the same size as the words of the program.
+        let mut auxv = Vec::new();
+        loop {
+            let key = self.read_word(&mut reader)?;
+            let val = self.read_word(&mut reader)?;
+            if self.is_compat {
+                auxv.extend_from_slice((key as u32).as_bytes());
+                auxv.extend_from_slice((val as u32).as_bytes());
+            } else {
+                auxv.extend_from_slice(key.as_bytes());
+                auxv.extend_from_slice(val.as_bytes());
+            }
+            if key == AuxKey::AT_NULL as usize {
+                break;
+            }
+        }
+
+        Ok(auxv)
+    }
+
     /// Returns the bottom address of the init stack (lowest address).
     pub const fn init_stack_bottom(&self) -> Vaddr {
         self.base
     }
+
+    /// Reads a word, which is zero-extended from u32 for a 32-bit program.
+    fn read_word(&self, reader: &mut VmReader<Infallible>) -> Result<usize> {
+        if self.is_compat {
+            Ok(reader.read_val::<u32>()? as usize)
+        } else {
+            Ok(reader.read_val::<usize>()?)
+        }
+    }
+
+    fn word_size(&self) -> usize {
+        if self.is_compat {
+            size_of::<u32>()
+        } else {
+            size_of::<usize>()
+        }
+    }
 }
@@ -9,12 +9,15 @@
 //! the basic info of process level vm segments,
 //! like init stack and heap.
 
+pub mod aslr;
 mod heap;
 mod init_stack;
 
 use aster_rights::Full;
 pub use heap::Heap;
+use ostd::mm::MAX_USERSPACE_VADDR;
 
+use self::aslr::Aslr;
 pub use self::{
     heap::USER_HEAP_SIZE_LIMIT,
     init_stack::{
@@ -23,12 +26,17 @@ pub use self::{
         MAX_ENV_LEN,
     },
 };
-use crate::{prelude::*, vm::vmar::Vmar};
+use crate::{
+    prelude::*,
+    process::personality::Personality,
+    vm::{perms::VmPerms, vmar::Vmar},
+};
 
 /*
  * The user's virtual memory space layout looks like below.
- * TODO: The layout of the userheap does not match the current implementation,
- * And currently the initial program break is a fixed value.
+ * TODO: The layout of the userheap does not match the current implementation.
+ * The initial program break is randomly padded above a fixed value, and the
+ * mappings are placed upwards from a random base above the heap (see `aslr`).
  *
  *  (high address)
  *  +---------------------+ <------+ The top of Vmar, which is the highest address usable
@@ -61,37 +69,59 @@ use crate::{prelude::*, vm::vmar::Vmar};
  *  (low address)
  */
 
+/// The highest address that the 32-bit programs can access, which is the same as Linux.
+#[cfg(target_arch = "x86_64")]
+pub const COMPAT_MAX_USERSPACE_VADDR: Vaddr = 0xffff_e000;
+
 // The process user space virtual memory
 pub struct ProcessVm {
     root_vmar: Vmar<Full>,
     init_stack: InitStack,
     heap: Heap,
-}
-
-impl Clone for ProcessVm {
-    fn clone(&self) -> Self {
-        Self {
-            root_vmar: self.root_vmar.dup().unwrap(),
-            init_stack: self.init_stack.clone(),
-            heap: self.heap.clone(),
-        }
-    }
+    aslr: Aslr,
 }
 
 impl ProcessVm {
     /// Allocates a new `ProcessVm`
     pub fn alloc() -> Self {
+        Self::alloc_with_personality(Personality::empty())
+    }
+
+    /// Allocates a new `ProcessVm` for a program that runs with the personality.
+    ///
+    /// The layout is randomized unless it is disabled by the personality or
+    /// `randomize_va_space`.
+    pub fn alloc_with_personality(personality: Personality) -> Self {
+        let aslr = Aslr::new(personality);
         let root_vmar = Vmar::<Full>::new_root();
-        let init_stack = InitStack::new();
-        let heap = Heap::new();
+        let init_stack = InitStack::new(aslr.stack_offset);
+        let heap = Heap::new_at(heap::USER_HEAP_BASE + aslr.brk_offset);
         heap.alloc_and_map_vm(&root_vmar).unwrap();
+        // The mappings are placed above the heap, so that they do not collide with the
+        // executables that are loaded to fixed addresses below the heap.
+        root_vmar.set_mmap_base(heap.reserved_range().end + aslr.mmap_offset);
         Self {
             root_vmar,
             heap,
             init_stack,
+            aslr,
         }
     }
 
+    /// Lays out the address space for a 32-bit program, which can only access the addresses
+    /// below [`COMPAT_MAX_USERSPACE_VADDR`].
+    ///
+    /// This method must be called before the program is loaded.
+    #[cfg(target_arch = "x86_64")]
+    pub(super) fn set_compat_layout(&self) {
+        let aslr = self.aslr.compat();
+        self.init_stack
+            .set_compat(COMPAT_MAX_USERSPACE_VADDR, aslr.stack_offset);
+        self.root_vmar
+            .set_mmap_base(self.heap.reserved_range().end + aslr.mmap_offset);
+        self.root_vmar.set_mmap_top(COMPAT_MAX_USERSPACE_VADDR);
+    }
+
     /// Forks a `ProcessVm` from `other`.
     ///
     /// The returned `ProcessVm` will have a forked `Vmar`.
@@ -101,6 +131,7 @@ impl ProcessVm {
             root_vmar,
             heap: other.heap.clone(),
             init_stack: other.init_stack.clone(),
+            aslr: other.aslr,
         })
     }
 
@@ -123,19 +154,24 @@ impl ProcessVm {
         &self,
         argv: Vec<CString>,
         envp: Vec<CString>,
+        execfn: CString,
         aux_vec: AuxVec,
+        perms: VmPerms,
     ) -> Result<()> {
         self.init_stack
-            .map_and_write(self.root_vmar(), argv, envp, aux_vec)
+            .map_and_write(self.root_vmar(), argv, envp, execfn, aux_vec, perms)
     }
 
-    pub(super) fn heap(&self) -> &Heap {
+    pub fn heap(&self) -> &Heap {
         &self.heap
     }
 
-    /// Clears existing mappings and then maps stack and heap vmo.
-    pub(super) fn clear_and_map(&self) {
-        self.root_vmar.clear().unwrap();
-        self.heap.alloc_and_map_vm(&self.root_vmar).unwrap();
+    /// Returns the size of the address space in bytes.
+    ///
+    /// The space reserved for the heap is not counted until the heap grows into it.
+    pub fn address_space_size(&self) -> usize {
+        let mapped_size = self.root_vmar.mapped_size(0..MAX_USERSPACE_VADDR);
+        let reserved_size = self.root_vmar.mapped_size(self.heap.reserved_range());
+        mapped_size - reserved_size
     }
 }
@@ -0,0 +1,361 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! The miscellaneous binary formats (binfmt_misc).
+//!
+//! A format tells the kernel to run the matching executables with a user-space interpreter,
+//! e.g., the binaries of foreign architectures with `qemu-user`, or the WebAssembly modules
+//! with a WebAssembly runtime. The formats are managed through the binfmt_misc filesystem
+//! (see [`crate::fs::binfmt_misc`]).
+//!
+//! A format is registered by writing `:name:type:offset:magic:mask:interpreter:flags` to the
+//! `register` file, where the first character can be any delimiter. The executables are
+//! recognized either by the magic bytes at the offset (type `M`) or by the extension of the
+//! file name (type `E`).
+
+use alloc::format;
+use core::sync::atomic::{AtomicBool, Ordering};
+
+use crate::{
+    fs::{
+        fs_resolver::{FsPath, FsResolver, AT_FDCWD},
+        path::Dentry,
+    },
+    prelude::*,
+};
+
+/// The number of bytes at the beginning of the executables that the magic bytes can match.
+pub const MAX_MAGIC_END: usize = 256;
+
+/// The maximum length of a registration string.
+const MAX_REGISTER_LEN: usize = 1920;
+
+bitflags! {
+    /// The flags of a format.
+    pub struct BinfmtFlags: u8 {
+        /// `P`: The original `argv[0]` is preserved after the path of the executable.
+        const PRESERVE_ARGV0 = 1 << 0;
+        /// `F`: The interpreter is opened when the format is registered, so it can be used
+        /// even in other mount namespaces or chroots.
+        const FIX_BINARY = 1 << 1;
+    }
+}
+
+/// How the executables of a format are recognized.
+#[derive(Debug)]
+enum Matcher {
+    Magic {
+        offset: usize,
+        magic: Vec<u8>,
+        mask: Option<Vec<u8>>,
+    },
+    Extension(String),
+}
+
+/// A registered format.
+#[derive(Debug)]
+pub struct BinfmtEntry {
+    name: String,
+    matcher: Matcher,
+    interpreter: String,
+    /// The opened interpreter if the format has [`BinfmtFlags::FIX_BINARY`].
+    interpreter_file: Option<Dentry>,
+    flags: BinfmtFlags,
+    is_enabled: AtomicBool,
+}
+
+impl BinfmtEntry {
+    /// Parses a registration string, opening the interpreter with `fs_resolver` if required.
+    fn parse(input: &str, fs_resolver: &FsResolver) -> Result<Self> {
+        let input = input.strip_suffix('\n').unwrap_or(input);
+        if input.len() < 11 || input.len() > MAX_REGISTER_LEN {
+            return_errno_with_message!(Errno::EINVAL, "the registration string is invalid");
+        }
+
+        let delimiter = input.chars().next().unwrap();
+        let fields: Vec<&str> = input[delimiter.len_utf8()..].split(delimiter).collect();
+        let &[name, type_, offset, magic, mask, interpreter, flags] = fields.as_slice() else {
+            return_errno_with_message!(Errno::EINVAL, "the registration string is invalid");
+        };
+
+        if name.is_empty() || name.contains('/') || name == "." || name == ".." {
+            return_errno_with_message!(Errno::EINVAL, "the name is invalid");
+        }
+
+        let matcher = match type_ {
+            "M" => {
+                let offset = if offset.is_empty() {
+                    0
+                } else {
+                    offset
+                        .parse::<usize>()
+                        .map_err(|_| Error::with_message(Errno::EINVAL, "the offset is invalid"))?
+                };
+                let magic = unescape(magic)?;
+                let mask = if mask.is_empty() {
+                    None
+                } else {
+                    Some(unescape(mask)?)
+                };
+                if magic.is_empty()
+                    || mask.as_ref().is_some_and(|mask| mask.len() != magic.len())
+                    || offset
+                        .checked_add(magic.len())
+                        .map_or(true, |end| end > MAX_MAGIC_END)
+                {
+                    return_errno_with_message!(Errno::EINVAL, "the magic is invalid");
+                }
+                Matcher::Magic {
+                    offset,
+                    magic,
+                    mask,
+                }
+            }
+            "E" => {
+                if !offset.is_empty() || !mask.is_empty() {
+                    return_errno_with_message!(
+                        Errno::EINVAL,
+                        "the offset and the mask are not allowed for extensions"
+                    );
+                }
+                if magic.is_empty() || magic.contains('/') {
+                    return_errno_with_message!(Errno::EINVAL, "the extension is invalid");
+                }
+                Matcher::Extension(magic.to_string())
+            }
+            _ => return_errno_with_message!(Errno::EINVAL, "the type is invalid"),
+        };
+
+        if interpreter.is_empty() {
+            return_errno_with_message!(Errno::EINVAL, "the interpreter is not specified");
+        }
+
+        let mut parsed_flags = BinfmtFlags::empty();
+        for flag in flags.chars() {
+            parsed_flags |= match flag {
+                'P' => BinfmtFlags::PRESERVE_ARGV0,
+                'F' => BinfmtFlags::FIX_BINARY,
+                // TODO: Support the `O` and `C` flags, which pass the opened executable to the
+                // interpreter and run it with the credentials of the executable.
+                _ => return_errno_with_message!(Errno::EINVAL, "the flag is not supported"),
+            };
+        }
+
+        let interpreter_file = if parsed_flags.contains(BinfmtFlags::FIX_BINARY) {
+            let fs_path = FsPath::new(AT_FDCWD, interpreter)?;
+            Some(fs_resolver.lookup(&fs_path)?)
+        } else {
+            None
+        };
+
+        Ok(Self {
+            name: name.to_string(),
+            matcher,
+            interpreter: interpreter.to_string(),
+            interpreter_file,
+            flags: parsed_flags,
+            is_enabled: AtomicBool::new(true),
+        })
+    }
+
+    /// Returns the name of the format.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Returns the path of the interpreter.
+    pub fn interpreter(&self) -> &str {
+        &self.interpreter
+    }
+
+    /// Returns the interpreter that was opened at registration, if any.
+    pub fn interpreter_file(&self) -> Option<&Dentry> {
+        self.interpreter_file.as_ref()
+    }
+
+    /// Returns the flags of the format.
+    pub fn flags(&self) -> BinfmtFlags {
+        self.flags
+    }
+
+    /// Returns whether the format is enabled.
+    pub fn is_enabled(&self) -> bool {
+        self.is_enabled.load(Ordering::Relaxed)
+    }
+
+    /// Enables or disables the format.
+    pub fn set_enabled(&self, is_enabled: bool) {
+        self.is_enabled.store(is_enabled, Ordering::Relaxed);
+    }
+
+    /// Returns whether the executable at `path`, which begins with `file_header`, is of the
+    /// format.
+    fn matches(&self, file_header: &[u8], path: &str) -> bool {
+        match &self.matcher {
+            Matcher::Magic {
+                offset,
+                magic,
+                mask,
+            } => {
+                let Some(bytes) = file_header.get(*offset..*offset + magic.len()) else {
+                    return false;
+                };
+                bytes
+                    .iter()
+                    .zip(magic)
+                    .enumerate()
+                    .all(|(i, (byte, magic))| {
+                        let mask = mask.as_ref().map_or(0xff, |mask| mask[i]);
+                        (byte ^ magic) & mask == 0
+                    })
+            }
+            Matcher::Extension(extension) => path
+                .rsplit_once('.')
+                .is_some_and(|(_, file_extension)| file_extension == extension),
+        }
+    }
+
+    /// Returns the content of the file of the format in the binfmt_misc filesystem.
+    pub fn status(&self) -> String {
+        let mut status = format!(
+            "{}\ninterpreter {}\nflags: ",
+            if self.is_enabled() {
+                "enabled"
+            } else {
+                "disabled"
+            },
+            self.interpreter
+        );
+        if self.flags.contains(BinfmtFlags::PRESERVE_ARGV0) {
+            status.push('P');
+        }
+        if self.flags.contains(BinfmtFlags::FIX_BINARY) {
+            status.push('F');
+        }
+        status.push('\n');
+
+        match &self.matcher {
+            Matcher::Magic {
+                offset,
+                magic,
+                mask,
+            } => {
+                status.push_str(&format!("offset {}\nmagic {}\n", offset, hex(magic)));
+                if let Some(mask) = mask {
+                    status.push_str(&format!("mask {}\n", hex(mask)));
+                }
+            }
+            Matcher::Extension(extension) => {
+                status.push_str(&format!("extension .{}\n", extension));
+            }
+        }
+        status
+    }
+}
+
+/// The registered formats.
+struct Formats {
+    is_enabled: bool,
+    /// The formats in the order of registration.
+    entries: Vec<Arc<BinfmtEntry>>,
+}
+
+static FORMATS: Mutex<Formats> = Mutex::new(Formats {
+    is_enabled: true,
+    entries: Vec::new(),
+});
+
+/// Registers a format with the registration string.
+pub fn register(input: &str, fs_resolver: &FsResolver) -> Result<Arc<BinfmtEntry>> {
+    let entry = Arc::new(BinfmtEntry::parse(input, fs_resolver)?);
+
+    let mut formats = FORMATS.lock();
+    if formats.entries.iter().any(|other| other.name == entry.name) {
+        return_errno_with_message!(Errno::EEXIST, "the format already exists");
+    }
+    formats.entries.push(entry.clone());
+    Ok(entry)
+}
+
+/// Unregisters the format with the name.
+pub fn unregister(name: &str) -> Result<()> {
+    let mut formats = FORMATS.lock();
+    let Some(index) = formats.entries.iter().position(|entry| entry.name == name) else {
+        return_errno_with_message!(Errno::ENOENT, "the format does not exist");
+    };
+    formats.entries.remove(index);
+    Ok(())
+}
+
+/// Unregisters all the formats.
+pub fn unregister_all() {
+    FORMATS.lock().entries.clear();
+}
+
+/// Returns the format with the name.
+pub fn lookup(name: &str) -> Option<Arc<BinfmtEntry>> {
+    FORMATS
+        .lock()
+        .entries
+        .iter()
+        .find(|entry| entry.name == name)
+        .cloned()
+}
+
+/// Returns all the registered formats.
+pub fn entries() -> Vec<Arc<BinfmtEntry>> {
+    FORMATS.lock().entries.clone()
+}
+
+/// Returns whether binfmt_misc is enabled.
+pub fn is_enabled() -> bool {
+    FORMATS.lock().is_enabled
+}
+
+/// Enables or disables binfmt_misc as a whole.
+pub fn set_enabled(is_enabled: bool) {
+    FORMATS.lock().is_enabled = is_enabled;
+}
+
+/// Finds the format of the executable at `path`, which begins with `file_header`.
+///
+/// Like Linux, the formats that are registered later take precedence.
+pub(super) fn match_format(file_header: &[u8], path: &str) -> Option<Arc<BinfmtEntry>> {
+    let formats = FORMATS.lock();
+    if !formats.is_enabled {
+        return None;
+    }
+    formats
+        .entries
+        .iter()
+        .rev()
+        .find(|entry| entry.is_enabled() && entry.matches(file_header, path))
+        .cloned()
+}
+
+/// Decodes the `\xHH` escape sequences in a string.
+fn unescape(input: &str) -> Result<Vec<u8>> {
+    let bytes = input.as_bytes();
+    let mut output = Vec::with_capacity(bytes.len());
+
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == b'\\' && bytes.get(i + 1) == Some(&b'x') {
+            let byte = bytes
+                .get(i + 2..i + 4)
+                .and_then(|hex| core::str::from_utf8(hex).ok())
+                .and_then(|hex| u8::from_str_radix(hex, 16).ok())
+                .ok_or_else(|| Error::with_message(Errno::EINVAL, "the escape is invalid"))?;
+            output.push(byte);
+            i += 4;
+        } else {
+            output.push(bytes[i]);
+            i += 1;
+        }
+    }
+
+    Ok(output)
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
@@ -3,45 +3,98 @@
 /// A wrapper of xmas_elf's elf parsing
 use xmas_elf::{
     header::{self, Header, HeaderPt1, HeaderPt2, HeaderPt2_, Machine_, Type_},
-    program::{self, ProgramHeader64},
+    program::{self, ProgramHeader32, ProgramHeader64},
 };
 
-use crate::prelude::*;
+use crate::{
+    fs::utils::{Inode, PATH_MAX},
+    prelude::*,
+};
+
+/// The size of the ELF header of a 64-bit ELF.
+const ELF_HEADER_SIZE: usize = 64;
+/// The offset of the program header offset (`e_phoff`) in the ELF header.
+const PH_OFFSET_FIELD: usize = 32;
+/// The size of the ELF header of a 32-bit ELF.
+const ELF32_HEADER_SIZE: usize = 52;
+/// The offset of the program header offset (`e_phoff`) in the ELF header of a 32-bit ELF.
+const ELF32_PH_OFFSET_FIELD: usize = 28;
+/// The maximum size of the program header table, which is the same as Linux.
+const MAX_PH_TABLE_SIZE: usize = 65536;
+/// The program header type that specifies the permissions of the stack.
+const PT_GNU_STACK: u32 = 0x6474_e551;
+
 pub struct Elf {
     pub elf_header: ElfHeader,
-    pub program_headers: Vec<ProgramHeader64>,
+    pub program_headers: Vec<ProgramHeader>,
 }
 
 impl Elf {
-    pub fn parse_elf(input: &[u8]) -> Result<Self> {
+    /// Parses the ELF header in `file_header`, which is read from the beginning of the file,
+    /// and the program headers.
+    ///
+    /// The program headers are read from `inode` if they are beyond `file_header`.
+    pub fn parse_elf(file_header: &[u8], inode: &Arc<dyn Inode>) -> Result<Self> {
         // first parse elf header
         // The elf header is usually 64 bytes. pt1 is 16bytes and pt2 is 48 bytes.
         // We require 128 bytes here is to keep consistency with linux implementations.
-        debug_assert!(input.len() >= 128);
-        let header = xmas_elf::header::parse_header(input)
+        debug_assert!(file_header.len() >= 128);
+        let header = xmas_elf::header::parse_header(file_header)
             .map_err(|_| Error::with_message(Errno::ENOEXEC, "parse elf header fails"))?;
         let elf_header = ElfHeader::parse_elf_header(header)?;
         check_elf_header(&elf_header)?;
+
         // than parse the program headers table
-        // FIXME: we should acquire enough pages before parse
-        let ph_offset = elf_header.pt2.ph_offset;
+        let ph_offset = elf_header.pt2.ph_offset as usize;
         let ph_count = elf_header.pt2.ph_count;
-        let ph_entry_size = elf_header.pt2.ph_entry_size;
-        debug_assert!(
-            input.len() >= ph_offset as usize + ph_count as usize * ph_entry_size as usize
-        );
-        let mut program_headers = Vec::with_capacity(ph_count as usize);
-        for index in 0..ph_count {
-            let program_header = xmas_elf::program::parse_program_header(input, header, index)
-                .map_err(|_| Error::with_message(Errno::ENOEXEC, "parse program header fails"))?;
-            let ph64 = match program_header {
-                xmas_elf::program::ProgramHeader::Ph64(ph64) => *ph64,
-                xmas_elf::program::ProgramHeader::Ph32(_) => {
-                    return_errno_with_message!(Errno::ENOEXEC, "Not 64 byte executable")
-                }
-            };
-            program_headers.push(ph64);
+        let (header_size, ph_offset_field, ph_entry_size) = if elf_header.is_compat() {
+            (
+                ELF32_HEADER_SIZE,
+                ELF32_PH_OFFSET_FIELD,
+                size_of::<ProgramHeader32>(),
+            )
+        } else {
+            (
+                ELF_HEADER_SIZE,
+                PH_OFFSET_FIELD,
+                size_of::<ProgramHeader64>(),
+            )
+        };
+        let ph_table_size = ph_count as usize * ph_entry_size;
+        // Like Linux, the size of the program header table is limited.
+        if elf_header.pt2.ph_entry_size as usize != ph_entry_size
+            || ph_count == 0
+            || ph_table_size > MAX_PH_TABLE_SIZE
+        {
+            return_errno_with_message!(Errno::ENOEXEC, "the program header table is invalid");
         }
+
+        let program_headers = if ph_offset
+            .checked_add(ph_table_size)
+            .is_some_and(|ph_end| ph_end <= file_header.len())
+        {
+            parse_program_headers(file_header, header, ph_count)?
+        } else {
+            // The program header table is not in `file_header`, so it is read into a new
+            // buffer right after the ELF header, whose program header offset is updated
+            // accordingly.
+            let mut buf = vec![0u8; header_size + ph_table_size];
+            buf[..header_size].copy_from_slice(&file_header[..header_size]);
+            if elf_header.is_compat() {
+                buf[ph_offset_field..ph_offset_field + size_of::<u32>()]
+                    .copy_from_slice(&(header_size as u32).to_le_bytes());
+            } else {
+                buf[ph_offset_field..ph_offset_field + size_of::<u64>()]
+                    .copy_from_slice(&(header_size as u64).to_le_bytes());
+            }
+            let read_len = inode.read_bytes_at(ph_offset, &mut buf[header_size..])?;
+            if read_len != ph_table_size {
+                return_errno_with_message!(Errno::ENOEXEC, "the program header table is truncated");
+            }
+            let header = xmas_elf::header::parse_header(&buf)
+                .map_err(|_| Error::with_message(Errno::ENOEXEC, "parse elf header fails"))?;
+            parse_program_headers(&buf, header, ph_count)?
+        };
         Ok(Self {
             elf_header,
             program_headers,
@@ -84,35 +137,117 @@ impl Elf {
         );
     }
 
+    /// Returns whether the ELF is a 32-bit program that runs in the compatibility mode.
+    pub fn is_compat(&self) -> bool {
+        self.elf_header.is_compat()
+    }
+
     /// whether the elf is a shared object
     pub fn is_shared_object(&self) -> bool {
         self.elf_header.pt2.type_.as_type() == header::Type::SharedObject
     }
 
-    /// read the ldso path from the elf interpret section
-    pub fn ldso_path(&self, file_header_buf: &[u8]) -> Result<Option<String>> {
+    /// Reads the path of the interpreter (i.e., the dynamic linker) from the file.
+    ///
+    /// This method returns `None` if the ELF does not have an interpreter, e.g., a statically
+    /// linked executable, either position-independent or not.
+    pub fn ldso_path(&self, inode: &Arc<dyn Inode>) -> Result<Option<String>> {
         for program_header in &self.program_headers {
-            let type_ = program_header.get_type().map_err(|_| {
-                Error::with_message(Errno::ENOEXEC, "parse program header type fails")
-            })?;
-            if type_ == program::Type::Interp {
+            if program_header.type_ == program::Type::Interp {
+                // Like Linux, the path must be a non-empty string shorter than `PATH_MAX`.
                 let file_size = program_header.file_size as usize;
-                let file_offset = program_header.offset as usize;
-                debug_assert!(file_offset + file_size <= file_header_buf.len());
-                let ldso = CStr::from_bytes_with_nul(
-                    &file_header_buf[file_offset..file_offset + file_size],
-                )?;
+                if !(2..=PATH_MAX).contains(&file_size) {
+                    return_errno_with_message!(Errno::ENOEXEC, "the interpreter path is invalid");
+                }
+                let mut buf = vec![0u8; file_size];
+                let read_len = inode.read_bytes_at(program_header.offset as usize, &mut buf)?;
+                if read_len != file_size {
+                    return_errno_with_message!(Errno::ENOEXEC, "the interpreter path is truncated");
+                }
+                let ldso = CStr::from_bytes_with_nul(&buf)?;
                 return Ok(Some(ldso.to_string_lossy().to_string()));
             }
         }
         Ok(None)
     }
 
-    // An offset to be subtracted from ELF vaddr for PIE
-    pub fn base_load_address_offset(&self) -> u64 {
-        let phdr = self.program_headers.first().unwrap();
-        phdr.virtual_addr - phdr.offset
+    /// Returns whether the stack should be executable.
+    ///
+    /// Like Linux on x86-64, the stack is executable only if the `PT_GNU_STACK` program header
+    /// requires it.
+    pub fn is_stack_executable(&self) -> bool {
+        self.program_headers.iter().any(|program_header| {
+            program_header.type_ == program::Type::OsSpecific(PT_GNU_STACK)
+                && program_header.flags.is_execute()
+        })
+    }
+}
+
+/// A program header.
+///
+/// The program headers of 32-bit ELFs are widened to the same fields as those of 64-bit ELFs.
+pub struct ProgramHeader {
+    pub type_: program::Type,
+    pub flags: program::Flags,
+    pub offset: u64,
+    pub virtual_addr: u64,
+    pub file_size: u64,
+    pub mem_size: u64,
+    pub align: u64,
+}
+
+impl TryFrom<&ProgramHeader64> for ProgramHeader {
+    type Error = Error;
+
+    fn try_from(ph64: &ProgramHeader64) -> Result<Self> {
+        Ok(Self {
+            type_: ph64.get_type().map_err(|_| {
+                Error::with_message(Errno::ENOEXEC, "parse program header type fails")
+            })?,
+            flags: ph64.flags,
+            offset: ph64.offset,
+            virtual_addr: ph64.virtual_addr,
+            file_size: ph64.file_size,
+            mem_size: ph64.mem_size,
+            align: ph64.align,
+        })
+    }
+}
+
+impl TryFrom<&ProgramHeader32> for ProgramHeader {
+    type Error = Error;
+
+    fn try_from(ph32: &ProgramHeader32) -> Result<Self> {
+        Ok(Self {
+            type_: ph32.get_type().map_err(|_| {
+                Error::with_message(Errno::ENOEXEC, "parse program header type fails")
+            })?,
+            flags: ph32.flags,
+            offset: ph32.offset as u64,
+            virtual_addr: ph32.virtual_addr as u64,
+            file_size: ph32.file_size as u64,
+            mem_size: ph32.mem_size as u64,
+            align: ph32.align as u64,
+        })
+    }
+}
+
+fn parse_program_headers(
+    input: &[u8],
+    header: Header,
+    ph_count: u16,
+) -> Result<Vec<ProgramHeader>> {
+    let mut program_headers = Vec::with_capacity(ph_count as usize);
+    for index in 0..ph_count {
+        let program_header = xmas_elf::program::parse_program_header(input, header, index)
+            .map_err(|_| Error::with_message(Errno::ENOEXEC, "parse program header fails"))?;
+        let program_header = match program_header {
+            xmas_elf::program::ProgramHeader::Ph64(ph64) => ProgramHeader::try_from(ph64)?,
+            xmas_elf::program::ProgramHeader::Ph32(ph32) => ProgramHeader::try_from(ph32)?,
+        };
+        program_headers.push(program_header);
     }
+    Ok(program_headers)
 }
 
 pub struct ElfHeader {
@@ -122,9 +257,9 @@ pub struct ElfHeader {
 
 impl ElfHeader {
     fn parse_elf_header(header: Header) -> Result<Self> {
-        let pt1 = *header.pt1;
-        let pt2 = match header.pt2 {
-            HeaderPt2::Header64(header_pt2) => {
+        // The fields of the 32-bit ELF headers are widened to 64 bits.
+        macro_rules! widen_header_pt2 {
+            ($header_pt2: expr) => {{
                 let HeaderPt2_ {
                     type_,
                     machine,
@@ -139,14 +274,14 @@ impl ElfHeader {
                     sh_entry_size,
                     sh_count,
                     sh_str_index,
-                } = header_pt2;
+                } = $header_pt2;
                 HeaderPt2_64 {
                     type_: *type_,
                     machine: *machine,
                     version: *version,
-                    entry_point: *entry_point,
-                    ph_offset: *ph_offset,
-                    sh_offset: *sh_offset,
+                    entry_point: (*entry_point).into(),
+                    ph_offset: (*ph_offset).into(),
+                    sh_offset: (*sh_offset).into(),
                     flags: *flags,
                     header_size: *header_size,
                     ph_entry_size: *ph_entry_size,
@@ -155,11 +290,20 @@ impl ElfHeader {
                     sh_count: *sh_count,
                     sh_str_index: *sh_str_index,
                 }
-            }
-            _ => return_errno_with_message!(Errno::ENOEXEC, "parse elf header failed"),
+            }};
+        }
+
+        let pt1 = *header.pt1;
+        let pt2 = match header.pt2 {
+            HeaderPt2::Header64(header_pt2) => widen_header_pt2!(header_pt2),
+            HeaderPt2::Header32(header_pt2) => widen_header_pt2!(header_pt2),
         };
         Ok(ElfHeader { pt1, pt2 })
     }
+
+    fn is_compat(&self) -> bool {
+        self.pt1.class() == header::Class::ThirtyTwo
+    }
 }
 
 pub struct HeaderPt2_64 {
@@ -188,14 +332,21 @@ pub struct HeaderPt2_64 {
 fn check_elf_header(elf_header: &ElfHeader) -> Result<()> {
     #[cfg(target_arch = "riscv64")]
     const EXPECTED_ELF_MACHINE: header::Machine = header::Machine::RISC_V;
+    #[cfg(target_arch = "aarch64")]
+    const EXPECTED_ELF_MACHINE: header::Machine = header::Machine::AArch64;
     #[cfg(target_arch = "x86_64")]
     const EXPECTED_ELF_MACHINE: header::Machine = header::Machine::X86_64;
+    // The 32-bit programs run in the compatibility mode.
+    #[cfg(target_arch = "x86_64")]
+    const COMPAT_ELF_MACHINE: Option<header::Machine> = Some(header::Machine::X86);
+    #[cfg(any(target_arch = "riscv64", target_arch = "aarch64"))]
+    const COMPAT_ELF_MACHINE: Option<header::Machine> = None;
 
-    // 64bit
-    debug_assert_eq!(elf_header.pt1.class(), header::Class::SixtyFour);
-    if elf_header.pt1.class() != header::Class::SixtyFour {
-        return_errno_with_message!(Errno::ENOEXEC, "Not 64 byte executable");
-    }
+    let expected_machine = match (elf_header.pt1.class(), COMPAT_ELF_MACHINE) {
+        (header::Class::SixtyFour, _) => EXPECTED_ELF_MACHINE,
+        (header::Class::ThirtyTwo, Some(compat_machine)) => compat_machine,
+        _ => return_errno_with_message!(Errno::ENOEXEC, "the ELF class is not supported"),
+    };
     // little endian
     debug_assert_eq!(elf_header.pt1.data(), header::Data::LittleEndian);
     if elf_header.pt1.data() != header::Data::LittleEndian {
@@ -206,7 +357,7 @@ fn check_elf_header(elf_header: &ElfHeader) -> Result<()> {
     // if elf_header.pt1.os_abi() != header::OsAbi::SystemV {
     //     return Error::new(Errno::ENOEXEC);
     // }
-    if elf_header.pt2.machine.as_machine() != EXPECTED_ELF_MACHINE {
+    if elf_header.pt2.machine.as_machine() != expected_machine {
         return_errno_with_message!(
             Errno::ENOEXEC,
             "Executable could not be run on this architecture"
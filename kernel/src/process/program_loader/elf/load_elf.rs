@@ -6,23 +6,31 @@
 //! This module is used to parse elf file content to get elf_load_info.
 //! When create a process from elf file, we will use the elf_load_info to construct the VmSpace
 
+use core::ops::Range;
+
 use align_ext::AlignExt;
 use aster_rights::Full;
-use ostd::mm::VmIo;
-use xmas_elf::program::{self, ProgramHeader64};
+use ostd::mm::{VmIo, MAX_USERSPACE_VADDR};
+use xmas_elf::program;
 
-use super::elf_file::Elf;
+use super::elf_file::{Elf, ProgramHeader};
+#[cfg(target_arch = "x86_64")]
+use crate::process::process_vm::COMPAT_MAX_USERSPACE_VADDR;
 use crate::{
+    arch::cpu::{elf_hwcap, elf_hwcap2},
     fs::{
         fs_resolver::{FsPath, FsResolver, AT_FDCWD},
         path::Dentry,
+        utils::Inode,
     },
     prelude::*,
     process::{
         posix_thread::do_exit_group,
         process_vm::{AuxKey, AuxVec, ProcessVm},
+        program_loader::ExecIds,
         TermStatus,
     },
+    time::timekeeping::USER_HZ,
     vdso::{vdso_vmo, VDSO_VMO_SIZE},
     vm::{perms::VmPerms, util::duplicate_frame, vmar::Vmar, vmo::VmoRightsOp},
 };
@@ -31,6 +39,7 @@ use crate::{
 ///
 /// This function will map elf segments and
 /// initialize process init stack.
+#[allow(clippy::too_many_arguments)]
 pub fn load_elf_to_vm(
     process_vm: &ProcessVm,
     file_header: &[u8],
@@ -38,28 +47,44 @@ pub fn load_elf_to_vm(
     fs_resolver: &FsResolver,
     argv: Vec<CString>,
     envp: Vec<CString>,
+    execfn: CString,
+    exec_ids: ExecIds,
 ) -> Result<ElfLoadInfo> {
-    let parsed_elf = Elf::parse_elf(file_header)?;
+    let parsed_elf = Elf::parse_elf(file_header, elf_file.inode())?;
+
+    let ldso = lookup_and_parse_ldso(&parsed_elf, elf_file.inode(), fs_resolver)?;
 
-    let ldso = lookup_and_parse_ldso(&parsed_elf, file_header, fs_resolver)?;
+    #[cfg(target_arch = "x86_64")]
+    if parsed_elf.is_compat() {
+        process_vm.set_compat_layout();
+    }
 
-    match init_and_map_vmos(process_vm, ldso, &parsed_elf, &elf_file) {
+    match init_and_map_vmos(process_vm, ldso, &parsed_elf, &elf_file, exec_ids) {
         Ok((entry_point, mut aux_vec)) => {
             // Map and set vdso entry.
             // Since vdso does not require being mapped to any specific address,
             // vdso is mapped after the elf file, heap and stack are mapped.
-            if let Some(vdso_text_base) = map_vdso_to_vm(process_vm) {
+            // TODO: Provide a 32-bit vdso for the programs in the compatibility mode.
+            if !parsed_elf.is_compat()
+                && let Some(vdso_text_base) = map_vdso_to_vm(process_vm)
+            {
                 aux_vec
                     .set(AuxKey::AT_SYSINFO_EHDR, vdso_text_base as u64)
                     .unwrap();
             }
 
-            process_vm.map_and_write_init_stack(argv, envp, aux_vec)?;
+            let stack_perms = if parsed_elf.is_stack_executable() {
+                VmPerms::READ | VmPerms::WRITE | VmPerms::EXEC
+            } else {
+                VmPerms::READ | VmPerms::WRITE
+            };
+            process_vm.map_and_write_init_stack(argv, envp, execfn, aux_vec, stack_perms)?;
 
             let user_stack_top = process_vm.user_stack_top();
             Ok(ElfLoadInfo {
                 entry_point,
                 user_stack_top,
+                is_compat: parsed_elf.is_compat(),
             })
         }
         Err(err) => {
@@ -83,11 +108,11 @@ pub fn load_elf_to_vm(
 
 fn lookup_and_parse_ldso(
     elf: &Elf,
-    file_header: &[u8],
+    inode: &Arc<dyn Inode>,
     fs_resolver: &FsResolver,
 ) -> Result<Option<(Dentry, Elf)>> {
     let ldso_file = {
-        let Some(ldso_path) = elf.ldso_path(file_header)? else {
+        let Some(ldso_path) = elf.ldso_path(inode)? else {
             return Ok(None);
         };
         let fs_path = FsPath::new(AT_FDCWD, &ldso_path)?;
@@ -97,16 +122,22 @@ fn lookup_and_parse_ldso(
         let mut buf = Box::new([0u8; PAGE_SIZE]);
         let inode = ldso_file.inode();
         inode.read_bytes_at(0, &mut *buf)?;
-        Elf::parse_elf(&*buf)?
+        Elf::parse_elf(&*buf, inode)?
     };
+    if ldso_elf.is_compat() != elf.is_compat() {
+        return_errno_with_message!(
+            Errno::ELIBBAD,
+            "the interpreter does not match the class of the executable"
+        );
+    }
     Ok(Some((ldso_file, ldso_elf)))
 }
 
 fn load_ldso(root_vmar: &Vmar<Full>, ldso_file: &Dentry, ldso_elf: &Elf) -> Result<LdsoLoadInfo> {
-    let map_addr = map_segment_vmos(ldso_elf, root_vmar, ldso_file)?;
+    let load_bias = map_segment_vmos(ldso_elf, root_vmar, ldso_file)?;
     Ok(LdsoLoadInfo::new(
-        ldso_elf.entry_point() + map_addr,
-        map_addr,
+        ldso_elf.entry_point() + load_bias,
+        load_bias,
     ))
 }
 
@@ -115,6 +146,7 @@ fn init_and_map_vmos(
     ldso: Option<(Dentry, Elf)>,
     parsed_elf: &Elf,
     elf_file: &Dentry,
+    exec_ids: ExecIds,
 ) -> Result<(Vaddr, AuxVec)> {
     let root_vmar = process_vm.root_vmar();
 
@@ -125,24 +157,22 @@ fn init_and_map_vmos(
         None
     };
 
-    let elf_map_addr = map_segment_vmos(parsed_elf, root_vmar, elf_file)?;
+    let elf_load_bias = map_segment_vmos(parsed_elf, root_vmar, elf_file)?;
 
     let aux_vec = {
         let ldso_base = ldso_load_info
             .as_ref()
             .map(|load_info| load_info.base_addr());
-        init_aux_vec(parsed_elf, elf_map_addr, ldso_base)?
+        init_aux_vec(parsed_elf, elf_load_bias, ldso_base, exec_ids)?
     };
 
     let entry_point = if let Some(ldso_load_info) = ldso_load_info {
-        // Normal shared object
+        // Dynamically linked executable
         ldso_load_info.entry_point()
-    } else if parsed_elf.is_shared_object() {
-        // ldso itself
-        parsed_elf.entry_point() + elf_map_addr
     } else {
-        // statically linked executable
-        parsed_elf.entry_point()
+        // Statically linked executable, ldso itself, or static PIE, where the load bias is zero
+        // unless the ELF is position-independent
+        parsed_elf.entry_point() + elf_load_bias
     };
 
     Ok((entry_point, aux_vec))
@@ -173,13 +203,15 @@ impl LdsoLoadInfo {
 pub struct ElfLoadInfo {
     entry_point: Vaddr,
     user_stack_top: Vaddr,
+    is_compat: bool,
 }
 
 impl ElfLoadInfo {
-    pub fn new(entry_point: Vaddr, user_stack_top: Vaddr) -> Self {
+    pub fn new(entry_point: Vaddr, user_stack_top: Vaddr, is_compat: bool) -> Self {
         Self {
             entry_point,
             user_stack_top,
+            is_compat,
         }
     }
 
@@ -190,49 +222,76 @@ impl ElfLoadInfo {
     pub fn user_stack_top(&self) -> Vaddr {
         self.user_stack_top
     }
+
+    /// Returns whether the program runs in the compatibility mode (see [`Elf::is_compat`]).
+    pub fn is_compat(&self) -> bool {
+        self.is_compat
+    }
 }
 
-/// Inits VMO for each segment and then map segment to root vmar
+/// Inits VMO for each segment and then map segment to root vmar.
+///
+/// Returns the load bias, i.e., the difference between the mapped addresses of the segments
+/// and their virtual addresses in the ELF, which is zero unless the ELF is a shared object.
 pub fn map_segment_vmos(elf: &Elf, root_vmar: &Vmar<Full>, elf_file: &Dentry) -> Result<Vaddr> {
     // all segments of the shared object must be mapped to a continuous vm range
     // to ensure the relative offset of each segment not changed.
-    let base_addr = if elf.is_shared_object() {
-        base_map_addr(elf, root_vmar)?
+    let load_bias = if elf.is_shared_object() {
+        let load_range = load_range(elf)?;
+        base_map_addr(&load_range, root_vmar)? - load_range.start
     } else {
         0
     };
+    // Like Linux, `PT_GNU_RELRO` is not handled here. The range is made read-only by the
+    // dynamic linker (or the startup code of static PIE) after the relocations are applied.
     for program_header in &elf.program_headers {
-        let type_ = program_header
-            .get_type()
-            .map_err(|_| Error::with_message(Errno::ENOEXEC, "parse program header type fails"))?;
-        if type_ == program::Type::Load {
+        if program_header.type_ == program::Type::Load {
             check_segment_align(program_header)?;
-            map_segment_vmo(program_header, elf_file, root_vmar, base_addr)?;
+            map_segment_vmo(program_header, elf_file, root_vmar, load_bias)?;
         }
     }
-    Ok(base_addr)
+    Ok(load_bias)
 }
 
-fn base_map_addr(elf: &Elf, root_vmar: &Vmar<Full>) -> Result<Vaddr> {
-    let elf_size = elf
-        .program_headers
-        .iter()
-        .filter_map(|program_header| {
-            if let Ok(type_) = program_header.get_type()
-                && type_ == program::Type::Load
-            {
-                let ph_max_addr = program_header.virtual_addr + program_header.mem_size;
-                Some(ph_max_addr as usize)
-            } else {
-                None
-            }
-        })
-        .max()
-        .ok_or(Error::with_message(
+/// Returns the page-aligned range of the virtual addresses that the loadable segments occupy.
+fn load_range(elf: &Elf) -> Result<Range<Vaddr>> {
+    let mut load_range: Option<Range<Vaddr>> = None;
+    for program_header in &elf.program_headers {
+        if program_header.type_ != program::Type::Load {
+            continue;
+        }
+        let start = program_header.virtual_addr as Vaddr;
+        let end = start
+            .checked_add(program_header.mem_size as usize)
+            .filter(|end| *end <= max_vaddr(elf))
+            .ok_or_else(|| Error::with_message(Errno::ENOEXEC, "the segment is too large"))?;
+        load_range = Some(match load_range {
+            Some(range) => range.start.min(start)..range.end.max(end),
+            None => start..end,
+        });
+    }
+
+    let Some(load_range) = load_range else {
+        return_errno_with_message!(
             Errno::ENOEXEC,
-            "executable file does not has loadable sections",
-        ))?;
-    let map_size = elf_size.align_up(PAGE_SIZE);
+            "executable file does not has loadable sections"
+        );
+    };
+    Ok(load_range.start.align_down(PAGE_SIZE)..load_range.end.align_up(PAGE_SIZE))
+}
+
+/// Returns the highest address that the program can access.
+fn max_vaddr(elf: &Elf) -> Vaddr {
+    #[cfg(target_arch = "x86_64")]
+    if elf.is_compat() {
+        return COMPAT_MAX_USERSPACE_VADDR;
+    }
+    MAX_USERSPACE_VADDR
+}
+
+/// Reserves a free VM range for the loadable segments and returns its start address.
+fn base_map_addr(load_range: &Range<Vaddr>, root_vmar: &Vmar<Full>) -> Result<Vaddr> {
+    let map_size = load_range.end - load_range.start;
     let vmar_map_options = root_vmar
         .new_map(map_size, VmPerms::empty())?
         .handle_page_faults_around();
@@ -242,10 +301,10 @@ fn base_map_addr(elf: &Elf, root_vmar: &Vmar<Full>) -> Result<Vaddr> {
 /// Creates and map the corresponding segment VMO to `root_vmar`.
 /// If needed, create additional anonymous mapping to represents .bss segment.
 fn map_segment_vmo(
-    program_header: &ProgramHeader64,
+    program_header: &ProgramHeader,
     elf_file: &Dentry,
     root_vmar: &Vmar<Full>,
-    base_addr: Vaddr,
+    load_bias: Vaddr,
 ) -> Result<()> {
     trace!(
         "mem range = 0x{:x} - 0x{:x}, mem_size = 0x{:x}",
@@ -299,7 +358,7 @@ fn map_segment_vmo(
     if page_offset != 0 {
         let new_frame = {
             let head_frame = segment_vmo.commit_page(segment_offset)?;
-            let new_frame = duplicate_frame(&head_frame)?;
+            let new_frame = duplicate_frame(&head_frame, None)?;
 
             let buffer = vec![0u8; page_offset];
             new_frame.write_bytes(0, &buffer).unwrap();
@@ -314,7 +373,7 @@ fn map_segment_vmo(
     if segment_size > tail_padding_offset {
         let new_frame = {
             let tail_frame = segment_vmo.commit_page(segment_offset + tail_padding_offset)?;
-            let new_frame = duplicate_frame(&tail_frame)?;
+            let new_frame = duplicate_frame(&tail_frame, None)?;
 
             let buffer = vec![0u8; (segment_size - tail_padding_offset) % PAGE_SIZE];
             new_frame
@@ -328,7 +387,7 @@ fn map_segment_vmo(
     }
 
     let perms = parse_segment_perm(program_header.flags);
-    let offset = base_addr + (program_header.virtual_addr as Vaddr).align_down(PAGE_SIZE);
+    let offset = load_bias + (program_header.virtual_addr as Vaddr).align_down(PAGE_SIZE);
     if segment_size != 0 {
         let mut vm_map_options = root_vmar
             .new_map(segment_size, perms)?
@@ -366,7 +425,7 @@ fn parse_segment_perm(flags: xmas_elf::program::Flags) -> VmPerms {
     vm_perm
 }
 
-fn check_segment_align(program_header: &ProgramHeader64) -> Result<()> {
+fn check_segment_align(program_header: &ProgramHeader) -> Result<()> {
     let align = program_header.align;
     if align == 0 || align == 1 {
         // no align requirement
@@ -381,28 +440,31 @@ fn check_segment_align(program_header: &ProgramHeader64) -> Result<()> {
     Ok(())
 }
 
-pub fn init_aux_vec(elf: &Elf, elf_map_addr: Vaddr, ldso_base: Option<Vaddr>) -> Result<AuxVec> {
+pub fn init_aux_vec(
+    elf: &Elf,
+    elf_load_bias: Vaddr,
+    ldso_base: Option<Vaddr>,
+    exec_ids: ExecIds,
+) -> Result<AuxVec> {
     let mut aux_vec = AuxVec::new();
+    aux_vec.set(AuxKey::AT_HWCAP, elf_hwcap())?;
+    aux_vec.set(AuxKey::AT_HWCAP2, elf_hwcap2())?;
     aux_vec.set(AuxKey::AT_PAGESZ, PAGE_SIZE as _)?;
-    let ph_addr = if elf.is_shared_object() {
-        elf.ph_addr()? + elf_map_addr
-    } else {
-        elf.ph_addr()?
-    };
-    aux_vec.set(AuxKey::AT_PHDR, ph_addr as u64)?;
+    aux_vec.set(AuxKey::AT_CLKTCK, USER_HZ as u64)?;
+    aux_vec.set(AuxKey::AT_PHDR, (elf.ph_addr()? + elf_load_bias) as u64)?;
     aux_vec.set(AuxKey::AT_PHNUM, elf.ph_count() as u64)?;
     aux_vec.set(AuxKey::AT_PHENT, elf.ph_ent() as u64)?;
-    let elf_entry = if elf.is_shared_object() {
-        let base_load_offset = elf.base_load_address_offset();
-        elf.entry_point() + elf_map_addr - base_load_offset as usize
-    } else {
-        elf.entry_point()
-    };
-    aux_vec.set(AuxKey::AT_ENTRY, elf_entry as u64)?;
+    // Like Linux, the base address is zero if there is no interpreter (e.g., static PIE).
+    aux_vec.set(AuxKey::AT_BASE, ldso_base.unwrap_or(0) as u64)?;
+    aux_vec.set(AuxKey::AT_FLAGS, 0)?;
+    aux_vec.set(AuxKey::AT_ENTRY, (elf.entry_point() + elf_load_bias) as u64)?;
+
+    aux_vec.set(AuxKey::AT_UID, u32::from(exec_ids.uid) as u64)?;
+    aux_vec.set(AuxKey::AT_EUID, u32::from(exec_ids.euid) as u64)?;
+    aux_vec.set(AuxKey::AT_GID, u32::from(exec_ids.gid) as u64)?;
+    aux_vec.set(AuxKey::AT_EGID, u32::from(exec_ids.egid) as u64)?;
+    aux_vec.set(AuxKey::AT_SECURE, exec_ids.is_secure() as u64)?;
 
-    if let Some(ldso_base) = ldso_base {
-        aux_vec.set(AuxKey::AT_BASE, ldso_base as u64)?;
-    }
     Ok(aux_vec)
 }
 
@@ -1,13 +1,15 @@
 // SPDX-License-Identifier: MPL-2.0
 
+pub mod binfmt_misc;
 pub mod elf;
 mod shebang;
 
 use self::{
+    binfmt_misc::BinfmtFlags,
     elf::{load_elf_to_vm, ElfLoadInfo},
     shebang::parse_shebang_line,
 };
-use super::process_vm::ProcessVm;
+use super::{process_vm::ProcessVm, Gid, Uid};
 use crate::{
     fs::{
         fs_resolver::{FsPath, FsResolver, AT_FDCWD},
@@ -15,58 +17,126 @@ use crate::{
         utils::{InodeType, Permission},
     },
     prelude::*,
+    security,
 };
 
+/// The maximum number of times that an executable can be redirected to an interpreter, which
+/// is the same as Linux.
+const MAX_INTERPRETER_DEPTH: usize = 5;
+
 /// Load an executable to root vmar, including loading programme image, preparing heap and stack,
 /// initializing argv, envp and aux tables.
-/// About recursion_limit: recursion limit is used to limit th recursion depth of shebang executables.
-/// If the interpreter(the program behind #!) of shebang executable is also a shebang,
-/// then it will trigger recursion. We will try to setup root vmar for the interpreter.
-/// I guess for most cases, setting the recursion_limit as 1 should be enough.
-/// because the interpreter is usually an elf binary(e.g., /bin/bash)
+/// The `process_vm` should be newly allocated, i.e., without any mappings of the old program.
+/// The `exec_ids` are the user and group IDs that the new program will run with.
+///
+/// If the executable is a script with a shebang line or it matches a binfmt_misc format, its
+/// interpreter is loaded instead, which may be redirected to another interpreter in turn.
 pub fn load_program_to_vm(
     process_vm: &ProcessVm,
     elf_file: Dentry,
     argv: Vec<CString>,
     envp: Vec<CString>,
     fs_resolver: &FsResolver,
-    recursion_limit: usize,
+    exec_ids: ExecIds,
 ) -> Result<(String, ElfLoadInfo)> {
-    let abs_path = elf_file.abs_path();
-    let inode = elf_file.inode();
-    let file_header = {
-        // read the first page of file header
-        let mut file_header_buffer = Box::new([0u8; PAGE_SIZE]);
-        inode.read_bytes_at(0, &mut *file_header_buffer)?;
-        file_header_buffer
-    };
-    if let Some(mut new_argv) = parse_shebang_line(&*file_header)? {
-        if recursion_limit == 0 {
-            return_errno_with_message!(Errno::ELOOP, "the recursieve limit is reached");
-        }
-        new_argv.extend_from_slice(&argv);
-        let interpreter = {
-            let filename = new_argv[0].to_str()?.to_string();
-            let fs_path = FsPath::new(AT_FDCWD, &filename)?;
-            fs_resolver.lookup(&fs_path)?
+    // Like Linux, `AT_EXECFN` refers to the executed file even if it has an interpreter.
+    let execfn = CString::new(elf_file.abs_path())?;
+
+    let mut elf_file = elf_file;
+    let mut argv = argv;
+    for _ in 0..=MAX_INTERPRETER_DEPTH {
+        let abs_path = elf_file.abs_path();
+        let file_header = {
+            // read the first page of file header
+            let mut file_header_buffer = Box::new([0u8; PAGE_SIZE]);
+            elf_file
+                .inode()
+                .read_bytes_at(0, &mut *file_header_buffer)?;
+            file_header_buffer
+        };
+
+        let Some((interpreter, new_argv)) =
+            find_interpreter(&*file_header, &abs_path, &argv, fs_resolver)?
+        else {
+            let elf_load_info = load_elf_to_vm(
+                process_vm,
+                &*file_header,
+                elf_file,
+                fs_resolver,
+                argv,
+                envp,
+                execfn,
+                exec_ids,
+            )?;
+            return Ok((abs_path, elf_load_info));
         };
         check_executable_file(&interpreter)?;
-        return load_program_to_vm(
-            process_vm,
-            interpreter,
-            new_argv,
-            envp,
-            fs_resolver,
-            recursion_limit - 1,
-        );
+        elf_file = interpreter;
+        argv = new_argv;
     }
 
-    process_vm.clear_and_map();
+    return_errno_with_message!(Errno::ELOOP, "too many levels of interpreters");
+}
 
-    let elf_load_info =
-        load_elf_to_vm(process_vm, &*file_header, elf_file, fs_resolver, argv, envp)?;
+/// Finds the interpreter of the executable at `path`, which begins with `file_header`, and
+/// returns the interpreter along with its arguments.
+///
+/// Like Linux, the binfmt_misc formats take precedence over the shebang line. The arguments
+/// of the interpreter are followed by the path of the executable, which replaces the original
+/// `argv[0]` unless the format preserves it.
+fn find_interpreter(
+    file_header: &[u8],
+    path: &str,
+    argv: &[CString],
+    fs_resolver: &FsResolver,
+) -> Result<Option<(Dentry, Vec<CString>)>> {
+    let lookup = |path: &str| fs_resolver.lookup(&FsPath::new(AT_FDCWD, path)?);
+    let path_arg = CString::new(path)?;
+    let remaining_args = argv.get(1..).unwrap_or_default();
 
-    Ok((abs_path, elf_load_info))
+    if let Some(format) = binfmt_misc::match_format(file_header, path) {
+        let interpreter = match format.interpreter_file() {
+            Some(interpreter) => interpreter.clone(),
+            None => lookup(format.interpreter())?,
+        };
+        let mut new_argv = vec![CString::new(format.interpreter())?, path_arg];
+        if format.flags().contains(BinfmtFlags::PRESERVE_ARGV0) {
+            new_argv.extend(argv.first().cloned());
+        }
+        new_argv.extend_from_slice(remaining_args);
+        return Ok(Some((interpreter, new_argv)));
+    }
+
+    if let Some(shebang) = parse_shebang_line(file_header)? {
+        let interpreter = lookup(shebang.interpreter.to_str()?)?;
+        let mut new_argv = vec![shebang.interpreter];
+        new_argv.extend(shebang.arg);
+        new_argv.push(path_arg);
+        new_argv.extend_from_slice(remaining_args);
+        return Ok(Some((interpreter, new_argv)));
+    }
+
+    Ok(None)
+}
+
+/// The user and group IDs that a new program runs with, which are passed to the program in
+/// the auxiliary vector.
+#[derive(Clone, Copy, Debug)]
+pub struct ExecIds {
+    pub uid: Uid,
+    pub euid: Uid,
+    pub gid: Gid,
+    pub egid: Gid,
+}
+
+impl ExecIds {
+    /// Returns whether the program runs in the secure mode (`AT_SECURE`).
+    ///
+    /// Like Linux, the program runs in the secure mode if it gains privileges, in which case
+    /// the dynamic linker ignores the dangerous environment variables such as `LD_PRELOAD`.
+    pub fn is_secure(&self) -> bool {
+        self.euid != self.uid || self.egid != self.gid
+    }
 }
 
 pub fn check_executable_file(dentry: &Dentry) -> Result<()> {
@@ -90,5 +160,7 @@ pub fn check_executable_file(dentry: &Dentry) -> Result<()> {
         return_errno_with_message!(Errno::EACCES, "the dentry is not executable");
     }
 
+    security::file_exec(dentry)?;
+
     Ok(())
 }
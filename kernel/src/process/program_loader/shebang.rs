@@ -2,32 +2,74 @@
 
 use crate::prelude::*;
 
+/// The maximum length of the shebang line that is parsed, which is the same as Linux.
+const MAX_SHEBANG_LINE_LEN: usize = 256;
+
+/// The interpreter specified by a shebang line.
+pub struct Shebang {
+    /// The path of the interpreter.
+    pub interpreter: CString,
+    /// The optional argument for the interpreter.
+    pub arg: Option<CString>,
+}
+
 /// Try to parse a buffer as a shebang line.
 ///
 /// If the buffer starts with `#!` and its header is a valid shebang sequence,
-/// then the function returns `Ok(Some(parts))`,
-/// where `parts` is a `Vec` that contains the path of and the arguments for the interpreter.
+/// then the function returns `Ok(Some(shebang))`.
 /// If the buffer starts with `#!` but some error occurs while parsing the file,
 /// then `Err(_)` is returned.
 /// If the buffer does not start with `#!`, then `Ok(None)` is returned.
-pub fn parse_shebang_line(file_header_buffer: &[u8]) -> Result<Option<Vec<CString>>> {
-    if !file_header_buffer.starts_with(b"#!") || !file_header_buffer.contains(&b'\n') {
+///
+/// Like Linux, the shebang line is `#!interpreter [optional-arg]`, where all the text after the
+/// interpreter path is passed to the interpreter as a single argument, and only the first
+/// [`MAX_SHEBANG_LINE_LEN`] bytes are parsed.
+pub fn parse_shebang_line(file_header_buffer: &[u8]) -> Result<Option<Shebang>> {
+    if !file_header_buffer.starts_with(b"#!") {
         // the file is not a shebang
         return Ok(None);
     }
-    let first_line_len = file_header_buffer.iter().position(|&c| c == b'\n').unwrap();
+
+    let buffer = &file_header_buffer[..file_header_buffer.len().min(MAX_SHEBANG_LINE_LEN)];
+    let line_end = buffer.iter().position(|&c| c == b'\n' || c == b'\0');
     // skip #!
-    let shebang_header = &file_header_buffer[2..first_line_len];
-    let mut shebang_argv = Vec::new();
-    for arg in shebang_header.split(|&c| c == b' ') {
-        let arg = CString::new(arg)?;
-        shebang_argv.push(arg);
+    let line = trim_start(&buffer[2..line_end.unwrap_or(buffer.len())]);
+
+    let interpreter_len = line.iter().position(is_blank).unwrap_or(line.len());
+    if interpreter_len == 0 {
+        return_errno_with_message!(Errno::ENOEXEC, "the interpreter is not specified");
     }
-    if shebang_argv.len() != 1 {
-        return_errno_with_message!(
-            Errno::EINVAL,
-            "One and only one interpreter program should be specified"
-        );
+    if line_end.is_none() && interpreter_len == line.len() {
+        return_errno_with_message!(Errno::ENOEXEC, "the interpreter path is too long");
     }
-    Ok(Some(shebang_argv))
+
+    let interpreter = CString::new(&line[..interpreter_len])?;
+    let arg = trim_end(trim_start(&line[interpreter_len..]));
+    let arg = if arg.is_empty() {
+        None
+    } else {
+        Some(CString::new(arg)?)
+    };
+
+    Ok(Some(Shebang { interpreter, arg }))
+}
+
+fn is_blank(c: &u8) -> bool {
+    *c == b' ' || *c == b'\t'
+}
+
+fn trim_start(bytes: &[u8]) -> &[u8] {
+    let start = bytes
+        .iter()
+        .position(|c| !is_blank(c))
+        .unwrap_or(bytes.len());
+    &bytes[start..]
+}
+
+fn trim_end(bytes: &[u8]) -> &[u8] {
+    let end = bytes
+        .iter()
+        .rposition(|c| !is_blank(c))
+        .map_or(0, |pos| pos + 1);
+    &bytes[..end]
 }
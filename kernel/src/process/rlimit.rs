@@ -1,14 +1,19 @@
 // SPDX-License-Identifier: MPL-2.0
-// FIXME: The resource limits should be respected by the corresponding subsystems of the kernel.
 
 #![allow(non_camel_case_types)]
 
-use super::process_vm::{INIT_STACK_SIZE, USER_HEAP_SIZE_LIMIT};
+use ostd::task::Task;
+
+use super::{
+    posix_thread::AsPosixThread,
+    process_vm::{INIT_STACK_SIZE, USER_HEAP_SIZE_LIMIT},
+    signal::{constants::SIGXFSZ, signals::kernel::KernelSignal},
+};
 use crate::prelude::*;
 
 // Constants for the boot-time rlimit defaults
 // See https://github.com/torvalds/linux/blob/fac04efc5c793dccbd07e2d59af9f90b7fc0dca4/include/asm-generic/resource.h#L11
-const RLIM_INFINITY: u64 = u64::MAX;
+pub const RLIM_INFINITY: u64 = u64::MAX;
 const INIT_RLIMIT_NPROC: u64 = 0;
 const INIT_RLIMIT_NICE: u64 = 0;
 const INIT_RLIMIT_SIGPENDING: u64 = 0;
@@ -21,6 +26,7 @@ const INIT_RLIMIT_MEMLOCK: u64 = 8 * 1024 * 1024;
 // https://github.com/torvalds/linux/blob/fac04efc5c793dccbd07e2d59af9f90b7fc0dca4/include/uapi/linux/mqueue.h#L26
 const INIT_RLIMIT_MSGQUEUE: u64 = 819200;
 
+#[derive(Clone)]
 pub struct ResourceLimits {
     rlimits: [RLimit64; RLIMIT_COUNT],
 }
@@ -72,6 +78,51 @@ impl Default for ResourceLimits {
     }
 }
 
+/// Checks whether the current thread can extend a regular file to `size` bytes.
+///
+/// If `size` exceeds `RLIMIT_FSIZE`, `SIGXFSZ` is sent to the current thread and `EFBIG` is
+/// returned.
+pub fn check_file_size(size: usize) -> Result<()> {
+    check_file_size_and_get_limit(size)?;
+    Ok(())
+}
+
+/// Checks whether the current thread can write `len` bytes at `offset` of a regular file.
+///
+/// Returns the number of bytes that can be written without exceeding `RLIMIT_FSIZE`. If no bytes
+/// can be written, `SIGXFSZ` is sent to the current thread and `EFBIG` is returned.
+pub fn check_file_write(offset: usize, len: usize) -> Result<usize> {
+    if len == 0 {
+        return Ok(0);
+    }
+
+    let limit = check_file_size_and_get_limit(offset.saturating_add(1))?;
+    Ok(len.min(limit - offset))
+}
+
+fn check_file_size_and_get_limit(size: usize) -> Result<usize> {
+    // Kernel threads are not limited.
+    let Some(current) = Task::current() else {
+        return Ok(usize::MAX);
+    };
+    let Some(posix_thread) = current.as_posix_thread() else {
+        return Ok(usize::MAX);
+    };
+
+    let limit = posix_thread
+        .process()
+        .resource_limits()
+        .lock()
+        .get_rlimit(ResourceType::RLIMIT_FSIZE)
+        .get_cur() as usize;
+    if size > limit {
+        posix_thread.enqueue_signal(Box::new(KernelSignal::new(SIGXFSZ)));
+        return_errno_with_message!(Errno::EFBIG, "the file size exceeds the limit");
+    }
+
+    Ok(limit)
+}
+
 #[repr(u32)]
 #[derive(Debug, Clone, Copy, TryFromInt)]
 pub enum ResourceType {
@@ -58,6 +58,46 @@ impl siginfo_t {
         // let siginfo = *self;
         read_union_fields!(self.siginfo_fields.sigfault.addr)
     }
+
+    pub fn set_si_pid_uid(&mut self, pid: Pid, uid: Uid) {
+        self.siginfo_fields.common.first.piduid = siginfo_piduid_t { pid, uid };
+    }
+
+    pub fn si_pid(&self) -> Pid {
+        read_union_fields!(self.siginfo_fields.common.first.piduid.pid)
+    }
+
+    pub fn si_uid(&self) -> Uid {
+        read_union_fields!(self.siginfo_fields.common.first.piduid.uid)
+    }
+
+    pub fn set_si_value(&mut self, value: sigval_t) {
+        self.siginfo_fields.common.second.value = value;
+    }
+
+    pub fn set_si_status(&mut self, status: i32) {
+        self.siginfo_fields.common.second.sigchild.status = status;
+    }
+
+    pub fn set_si_sigsys(&mut self, call_addr: Vaddr, syscall: i32, arch: u32) {
+        self.siginfo_fields.sigsys = siginfo_sigsys_t {
+            call_addr,
+            syscall,
+            arch,
+        };
+    }
+
+    pub fn set_si_timer(&mut self, timer_id: i32, overrun: i32, value: sigval_t) {
+        self.siginfo_fields.common = siginfo_common_t {
+            first: siginfo_common_first_t {
+                timer: siginfo_timer_t {
+                    timerid: timer_id,
+                    overrun,
+                },
+            },
+            second: siginfo_common_second_t { value },
+        };
+    }
 }
 
 #[derive(Clone, Copy, Pod)]
@@ -66,6 +106,7 @@ union siginfo_fields_t {
     bytes: [u8; 128 - mem::size_of::<i32>() * 4],
     common: siginfo_common_t,
     sigfault: siginfo_sigfault_t,
+    sigsys: siginfo_sigsys_t,
 }
 
 impl siginfo_fields_t {
@@ -78,7 +119,7 @@ impl siginfo_fields_t {
 
 #[derive(Clone, Copy, Pod)]
 #[repr(C)]
-union siginfo_common_t {
+struct siginfo_common_t {
     first: siginfo_common_first_t,
     second: siginfo_common_second_t,
 }
@@ -119,6 +160,12 @@ pub union sigval_t {
 }
 
 impl sigval_t {
+    pub fn from_int(sigval_int: i32) -> Self {
+        let mut value = Self::new_zeroed();
+        value.sigval_int = sigval_int;
+        value
+    }
+
     pub fn read_int(&self) -> i32 {
         read_union_fields!(self.sigval_int)
     }
@@ -144,6 +191,14 @@ struct siginfo_sigfault_t {
     first: siginfo_sigfault_first_t,
 }
 
+#[derive(Clone, Copy, Pod)]
+#[repr(C)]
+struct siginfo_sigsys_t {
+    call_addr: Vaddr, // *const c_void
+    syscall: i32,
+    arch: u32,
+}
+
 #[derive(Clone, Copy, Pod)]
 #[repr(C)]
 union siginfo_sigfault_first_t {
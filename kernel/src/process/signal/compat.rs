@@ -0,0 +1,380 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! The signal frames of the 32-bit (i386) programs.
+//!
+//! Like Linux, a handler that is registered with `SA_SIGINFO` gets the `rt_sigframe`, which
+//! is restored by `rt_sigreturn`. Other handlers get the legacy `sigframe`, which is restored by
+//! `sigreturn`. Both frames follow the i386 layout, where the registers, the pointers, and the
+//! fields of `siginfo_t` are 32-bit.
+//!
+//! TODO: Save the FPU state in the signal frames.
+
+use core::{
+    mem::{offset_of, size_of},
+    sync::atomic::Ordering,
+};
+
+use align_ext::AlignExt;
+use ostd::{cpu::UserContext, user::UserContextApi};
+
+use super::{
+    c_types::{siginfo_t, stack_t},
+    constants::*,
+    sig_action::SigActionFlags,
+    sig_mask::SigMask,
+    sig_num::SigNum,
+    SigStack,
+};
+use crate::prelude::*;
+
+/// The `sigcontext` structure of the 32-bit programs.
+///
+/// The segment selectors other than GS are not recorded, since they cannot be changed by the
+/// 32-bit programs.
+#[repr(C)]
+#[derive(Debug, Default, Clone, Copy, Pod)]
+struct CompatSigContext {
+    gs: u32,
+    fs: u32,
+    es: u32,
+    ds: u32,
+    di: u32,
+    si: u32,
+    bp: u32,
+    sp: u32,
+    bx: u32,
+    dx: u32,
+    cx: u32,
+    ax: u32,
+    trapno: u32,
+    err: u32,
+    ip: u32,
+    cs: u32,
+    flags: u32,
+    sp_at_signal: u32,
+    ss: u32,
+    fpstate: u32,
+    oldmask: u32,
+    cr2: u32,
+}
+
+/// The flags that can be restored from [`CompatSigContext`], which are the same as Linux.
+///
+/// They are AC, OF, DF, TF, SF, ZF, AF, PF, CF, and RF.
+const FIX_EFLAGS: usize = 0x5_0dd5;
+
+impl CompatSigContext {
+    fn new(user_ctx: &UserContext, oldmask: u32) -> Self {
+        let regs = user_ctx.general_regs();
+        Self {
+            gs: user_ctx.gs_selector() as u32,
+            di: regs.rdi as u32,
+            si: regs.rsi as u32,
+            bp: regs.rbp as u32,
+            sp: regs.rsp as u32,
+            bx: regs.rbx as u32,
+            dx: regs.rdx as u32,
+            cx: regs.rcx as u32,
+            ax: regs.rax as u32,
+            ip: regs.rip as u32,
+            flags: regs.rflags as u32,
+            sp_at_signal: regs.rsp as u32,
+            oldmask,
+            ..Default::default()
+        }
+    }
+
+    fn restore(&self, user_ctx: &mut UserContext) {
+        let regs = user_ctx.general_regs_mut();
+        regs.rdi = self.di as usize;
+        regs.rsi = self.si as usize;
+        regs.rbp = self.bp as usize;
+        regs.rsp = self.sp as usize;
+        regs.rbx = self.bx as usize;
+        regs.rdx = self.dx as usize;
+        regs.rcx = self.cx as usize;
+        regs.rax = self.ax as usize;
+        regs.rip = self.ip as usize;
+        // Other flags (e.g., IOPL) cannot be changed by the user space.
+        regs.rflags = (regs.rflags & !FIX_EFLAGS) | (self.flags as usize & FIX_EFLAGS);
+
+        // The selector is checked before it is loaded.
+        user_ctx.set_gs_selector(self.gs as u16);
+    }
+}
+
+/// The `stack_t` structure of the 32-bit programs.
+#[repr(C)]
+#[derive(Debug, Default, Clone, Copy, Pod)]
+struct CompatStack {
+    sp: u32,
+    flags: i32,
+    size: u32,
+}
+
+impl From<stack_t> for CompatStack {
+    fn from(stack: stack_t) -> Self {
+        Self {
+            sp: stack.ss_sp as u32,
+            flags: stack.ss_flags,
+            size: stack.ss_size as u32,
+        }
+    }
+}
+
+impl From<CompatStack> for stack_t {
+    fn from(stack: CompatStack) -> Self {
+        Self {
+            ss_sp: stack.sp as Vaddr,
+            ss_flags: stack.flags,
+            ss_size: stack.size as usize,
+        }
+    }
+}
+
+/// The `ucontext_t` structure of the 32-bit programs.
+#[repr(C)]
+#[derive(Debug, Default, Clone, Copy, Pod)]
+struct CompatUcontext {
+    flags: u32,
+    link: u32,
+    stack: CompatStack,
+    mcontext: CompatSigContext,
+    sigmask: [u32; 2],
+}
+
+/// The `siginfo_t` structure of the 32-bit programs.
+#[repr(C)]
+#[derive(Clone, Copy, Pod)]
+struct CompatSigInfo {
+    signo: i32,
+    errno: i32,
+    code: i32,
+    fields: [u32; 29],
+}
+
+impl CompatSigInfo {
+    /// Converts the 64-bit `siginfo_t`.
+    ///
+    /// Like Linux, the layout of the fields is determined by the signal and the code.
+    fn new(sig_num: SigNum, info: &siginfo_t) -> Self {
+        // The fields of the 64-bit `siginfo_t` start at the offset 16.
+        let bytes = info.as_bytes();
+        let read_u32 = |offset: usize| {
+            u32::from_ne_bytes(bytes[16 + offset..16 + offset + 4].try_into().unwrap())
+        };
+
+        let mut fields = [0u32; 29];
+        let code = info.si_code;
+        if code > SI_USER && code < SI_KERNEL && sig_num == SIGCHLD {
+            // `si_pid`, `si_uid`, and `si_status`.
+            fields[0] = read_u32(0);
+            fields[1] = read_u32(4);
+            fields[2] = read_u32(8);
+        } else if code > SI_USER
+            && code < SI_KERNEL
+            && [SIGILL, SIGFPE, SIGSEGV, SIGBUS, SIGTRAP].contains(&sig_num)
+        {
+            // `si_addr`.
+            fields[0] = read_u32(0);
+        } else if code == SYS_SECCOMP && sig_num == SIGSYS {
+            // `si_call_addr`, `si_syscall`, and `si_arch`.
+            fields[0] = read_u32(0);
+            fields[1] = read_u32(8);
+            fields[2] = read_u32(12);
+        } else if code > SI_USER && code < SI_KERNEL && sig_num == SIGIO {
+            // `si_band` and `si_fd`.
+            fields[0] = read_u32(0);
+            fields[1] = read_u32(8);
+        } else {
+            // `si_pid` and `si_uid` (or `si_timerid` and `si_overrun`), and `si_value`.
+            fields[0] = read_u32(0);
+            fields[1] = read_u32(4);
+            fields[2] = read_u32(8);
+        }
+
+        Self {
+            signo: info.si_signo,
+            errno: info.si_errno,
+            code,
+            fields,
+        }
+    }
+}
+
+/// The `rt_sigframe` structure of the 32-bit programs.
+#[repr(C)]
+#[derive(Clone, Copy, Pod)]
+struct CompatRtSigFrame {
+    pretcode: u32,
+    sig: i32,
+    pinfo: u32,
+    puc: u32,
+    info: CompatSigInfo,
+    uc: CompatUcontext,
+    retcode: [u8; 8],
+}
+
+/// The legacy `sigframe` structure of the 32-bit programs.
+#[repr(C)]
+#[derive(Clone, Copy, Pod)]
+struct CompatSigFrame {
+    pretcode: u32,
+    sig: i32,
+    sc: CompatSigContext,
+    // Like Linux, the FPU state is not saved here, but the field is kept so that the offset of
+    // `extramask` is unchanged.
+    fpstate_unused: [u32; 156],
+    extramask: u32,
+    retcode: [u8; 8],
+}
+
+/// The code that calls `rt_sigreturn` if no restorer is provided.
+///
+/// It is `movl $173, %eax; int $0x80`.
+const RT_SIGRETURN_CODE: [u8; 8] = [0xb8, 0xad, 0x00, 0x00, 0x00, 0xcd, 0x80, 0x00];
+
+/// The code that calls `sigreturn` if no restorer is provided.
+///
+/// It is `popl %eax; movl $119, %eax; int $0x80`.
+const SIGRETURN_CODE: [u8; 8] = [0x58, 0xb8, 0x77, 0x00, 0x00, 0x00, 0xcd, 0x80];
+
+/// Sets up the signal frame for a 32-bit program and sets the registers to run the handler.
+///
+/// `stack_pointer` is the top of the stack to put the frame, and `uc_stack` is the alternate
+/// signal stack that is restored when the handler returns.
+#[allow(clippy::too_many_arguments)]
+pub(super) fn setup_frame(
+    ctx: &Context,
+    sig_num: SigNum,
+    handler_addr: Vaddr,
+    flags: SigActionFlags,
+    restorer_addr: Vaddr,
+    old_mask: SigMask,
+    uc_stack: stack_t,
+    stack_pointer: Vaddr,
+    user_ctx: &mut UserContext,
+    sig_info: &siginfo_t,
+) -> Result<()> {
+    let user_space = ctx.user_space();
+    let old_mask = u64::from(old_mask);
+    let sig = sig_num.as_u8() as i32;
+
+    let (frame_addr, info_addr, uc_addr) = if flags.contains(SigActionFlags::SA_SIGINFO) {
+        let frame_addr = frame_addr(stack_pointer, size_of::<CompatRtSigFrame>())?;
+        let info_addr = frame_addr + offset_of!(CompatRtSigFrame, info);
+        let uc_addr = frame_addr + offset_of!(CompatRtSigFrame, uc);
+        let pretcode = if flags.contains(SigActionFlags::SA_RESTORER) {
+            restorer_addr
+        } else {
+            frame_addr + offset_of!(CompatRtSigFrame, retcode)
+        };
+
+        let frame = CompatRtSigFrame {
+            pretcode: pretcode as u32,
+            sig,
+            pinfo: info_addr as u32,
+            puc: uc_addr as u32,
+            info: CompatSigInfo::new(sig_num, sig_info),
+            uc: CompatUcontext {
+                stack: CompatStack::from(uc_stack),
+                mcontext: CompatSigContext::new(user_ctx, 0),
+                sigmask: [old_mask as u32, (old_mask >> 32) as u32],
+                ..Default::default()
+            },
+            retcode: RT_SIGRETURN_CODE,
+        };
+        user_space.write_val(frame_addr, &frame)?;
+
+        (frame_addr, info_addr, uc_addr)
+    } else {
+        let frame_addr = frame_addr(stack_pointer, size_of::<CompatSigFrame>())?;
+        let pretcode = if flags.contains(SigActionFlags::SA_RESTORER) {
+            restorer_addr
+        } else {
+            frame_addr + offset_of!(CompatSigFrame, retcode)
+        };
+
+        let frame = CompatSigFrame {
+            pretcode: pretcode as u32,
+            sig,
+            sc: CompatSigContext::new(user_ctx, old_mask as u32),
+            fpstate_unused: [0; 156],
+            extramask: (old_mask >> 32) as u32,
+            retcode: SIGRETURN_CODE,
+        };
+        user_space.write_val(frame_addr, &frame)?;
+
+        (frame_addr, 0, 0)
+    };
+
+    // The handler takes the arguments from the stack, or from the registers if it is compiled
+    // with `-mregparm=3`.
+    let regs = user_ctx.general_regs_mut();
+    regs.rip = handler_addr;
+    regs.rsp = frame_addr;
+    regs.rax = sig as usize;
+    regs.rdx = info_addr;
+    regs.rcx = uc_addr;
+    // Clear `DF` flag for C function entry to conform to the i386 calling convention.
+    const X86_EFLAGS_DF: usize = 1 << 10;
+    regs.rflags &= !X86_EFLAGS_DF;
+
+    Ok(())
+}
+
+/// Allocates the signal frame below `stack_pointer`.
+///
+/// Like Linux, the frame is aligned so that the stack pointer is 16-byte aligned after the
+/// return address (i.e., `pretcode`) is popped.
+fn frame_addr(stack_pointer: Vaddr, size: usize) -> Result<Vaddr> {
+    let Some(frame_addr) = (stack_pointer as u32 as Vaddr).checked_sub(size) else {
+        return_errno_with_message!(Errno::EFAULT, "the stack overflows");
+    };
+    Ok((frame_addr + 4).align_down(16) - 4)
+}
+
+/// Restores the context from the `rt_sigframe` of a 32-bit program.
+pub fn restore_rt_frame(ctx: &Context, user_ctx: &mut UserContext) -> Result<()> {
+    // The return address has been popped.
+    let frame_addr = user_ctx.stack_pointer().wrapping_sub(4) as u32 as Vaddr;
+    let uc = ctx
+        .user_space()
+        .read_val::<CompatUcontext>(frame_addr + offset_of!(CompatRtSigFrame, uc))?;
+
+    // Restore the alternate signal stack, which may have been disabled by `SS_AUTODISARM`. Like
+    // Linux, errors are ignored (e.g., the stack cannot be changed since we are still on it).
+    if let Ok(saved_stack) = SigStack::try_from(stack_t::from(uc.stack)) {
+        let mut sig_stack = ctx.thread_local.sig_stack().borrow_mut();
+        if !sig_stack.is_on_stack(user_ctx.stack_pointer()) {
+            *sig_stack = saved_stack;
+        }
+    }
+
+    uc.mcontext.restore(user_ctx);
+    restore_sig_mask(ctx, uc.sigmask[0], uc.sigmask[1]);
+
+    Ok(())
+}
+
+/// Restores the context from the legacy `sigframe` of a 32-bit program.
+pub fn restore_frame(ctx: &Context, user_ctx: &mut UserContext) -> Result<()> {
+    // The return address and the signal number have been popped.
+    let frame_addr = user_ctx.stack_pointer().wrapping_sub(8) as u32 as Vaddr;
+    let frame = ctx.user_space().read_val::<CompatSigFrame>(frame_addr)?;
+
+    frame.sc.restore(user_ctx);
+    restore_sig_mask(ctx, frame.sc.oldmask, frame.extramask);
+
+    Ok(())
+}
+
+fn restore_sig_mask(ctx: &Context, low: u32, high: u32) {
+    // `SIGKILL` and `SIGSTOP` can never be blocked.
+    let mut sig_mask = SigMask::from((high as u64) << 32 | low as u64);
+    sig_mask -= SIGKILL;
+    sig_mask -= SIGSTOP;
+    ctx.posix_thread
+        .sig_mask()
+        .store(sig_mask, Ordering::Relaxed);
+}
@@ -1,6 +1,8 @@
 // SPDX-License-Identifier: MPL-2.0
 
 pub mod c_types;
+#[cfg(target_arch = "x86_64")]
+pub mod compat;
 pub mod constants;
 mod events;
 mod pause;
@@ -17,7 +19,7 @@ use core::{mem, sync::atomic::Ordering};
 
 use align_ext::AlignExt;
 use c_types::{siginfo_t, ucontext_t};
-use constants::SIGKILL;
+use constants::{SIGCONT, SIGKILL, SIGSTOP};
 pub use events::{SigEvents, SigEventsFilter};
 use ostd::{cpu::UserContext, user::UserContextApi};
 pub use pause::{with_signal_blocked, Pause};
@@ -27,12 +29,11 @@ use sig_mask::SigMask;
 use sig_num::SigNum;
 pub use sig_stack::{SigStack, SigStackFlags};
 
-use super::posix_thread::ThreadLocal;
 use crate::{
     cpu::LinuxAbi,
     current_userspace,
     prelude::*,
-    process::{posix_thread::do_exit_group, TermStatus},
+    process::{coredump::do_coredump, posix_thread::do_exit_group, TermStatus},
 };
 
 pub trait SignalContext {
@@ -55,19 +56,37 @@ pub fn handle_pending_signal(
         if let Some(signal) = posix_thread.dequeue_signal(&sig_mask) {
             signal
         } else {
+            restart_syscall_if_interrupted(user_ctx, syscall_number);
             return;
         }
     };
 
+    // The tracer decides whether and which signal is delivered, except for `SIGKILL`.
+    let signal = if signal.num() == SIGKILL {
+        signal
+    } else {
+        let Some(signal) = posix_thread.ptrace().stop_at_signal(ctx, user_ctx, signal) else {
+            restart_syscall_if_interrupted(user_ctx, syscall_number);
+            return;
+        };
+        signal
+    };
+
     let sig_num = signal.num();
     trace!("sig_num = {:?}, sig_name = {}", sig_num, sig_num.sig_name());
     let current = posix_thread.process();
+    // Like Linux, `SIGCONT` continues the stopped process even if it is ignored or handled.
+    if sig_num == SIGCONT {
+        current.resume();
+    }
+
     let mut sig_dispositions = current.sig_dispositions().lock();
     let sig_action = sig_dispositions.get(sig_num);
     trace!("sig action: {:x?}", sig_action);
     match sig_action {
         SigAction::Ign => {
             trace!("Ignore signal {:?}", sig_num);
+            restart_syscall_if_interrupted(user_ctx, syscall_number);
         }
         SigAction::User {
             handler_addr,
@@ -114,7 +133,20 @@ pub fn handle_pending_signal(
             let sig_default_action = SigDefaultAction::from_signum(sig_num);
             trace!("sig_default_action: {:?}", sig_default_action);
             match sig_default_action {
-                SigDefaultAction::Core | SigDefaultAction::Term => {
+                SigDefaultAction::Core => {
+                    warn!(
+                        "{:?}: terminating on signal {}",
+                        current.executable_path(),
+                        sig_num.sig_name()
+                    );
+                    let term_status = if do_coredump(ctx, user_ctx, &signal.to_info()) {
+                        TermStatus::CoreDumped(sig_num)
+                    } else {
+                        TermStatus::Killed(sig_num)
+                    };
+                    do_exit_group(term_status);
+                }
+                SigDefaultAction::Term => {
                     warn!(
                         "{:?}: terminating on signal {}",
                         current.executable_path(),
@@ -123,18 +155,39 @@ pub fn handle_pending_signal(
                     // We should exit current here, since we cannot restore a valid status from trap now.
                     do_exit_group(TermStatus::Killed(sig_num));
                 }
-                SigDefaultAction::Ign => {}
-                SigDefaultAction::Stop => {
-                    let _ = ctx.thread.stop();
+                SigDefaultAction::Ign | SigDefaultAction::Cont => {
+                    restart_syscall_if_interrupted(user_ctx, syscall_number);
                 }
-                SigDefaultAction::Cont => {
-                    let _ = ctx.thread.resume();
+                SigDefaultAction::Stop => {
+                    // The processes in an orphaned process group are not stopped by the signals
+                    // from terminals, since no process in the session could continue them.
+                    let is_orphaned = sig_num != SIGSTOP
+                        && current
+                            .process_group()
+                            .is_some_and(|process_group| process_group.is_orphaned());
+                    if !is_orphaned {
+                        current.stop(sig_num);
+                    }
+                    restart_syscall_if_interrupted(user_ctx, syscall_number);
                 }
             }
         }
     }
 }
 
+/// Restarts the system call if it is interrupted by a signal, but no user handler is run.
+///
+/// Since the signal is invisible to the user space, the system call is restarted as if it had
+/// never been interrupted.
+fn restart_syscall_if_interrupted(user_ctx: &mut UserContext, syscall_number: Option<usize>) {
+    if let Some(syscall_number) = syscall_number
+        && user_ctx.syscall_ret() == -(Errno::ERESTARTSYS as i32) as usize
+    {
+        user_ctx.set_syscall_num(syscall_number);
+        user_ctx.set_instruction_pointer(user_ctx.instruction_pointer() - 2);
+    }
+}
+
 #[allow(clippy::too_many_arguments)]
 pub fn handle_user_signal(
     ctx: &Context,
@@ -167,13 +220,44 @@ pub fn handle_user_signal(
         .store(old_mask + mask, Ordering::Relaxed);
 
     // Set up signal stack.
-    let mut stack_pointer = if let Some(sp) = use_alternate_signal_stack(ctx.thread_local) {
-        sp as u64
-    } else {
-        // just use user stack
-        user_ctx.stack_pointer() as u64
+    let (mut stack_pointer, uc_stack) = {
+        let user_sp = user_ctx.stack_pointer();
+        let mut sig_stack = ctx.thread_local.sig_stack().borrow_mut();
+
+        let handler_sp = if flags.contains(SigActionFlags::SA_ONSTACK) {
+            sig_stack.handler_stack_pointer(user_sp)
+        } else {
+            None
+        };
+
+        // With `SS_AUTODISARM`, the stack is disabled while the handler is running, so that the
+        // handler can switch away from it (e.g., by `swapcontext`) and the next signal still
+        // gets a fresh stack. The saved stack is restored by `sigreturn`.
+        let uc_stack = sig_stack.as_saved_c_type();
+        if sig_stack.flags().contains(SigStackFlags::SS_AUTODISARM) {
+            *sig_stack = SigStack::default();
+        }
+
+        (handler_sp.unwrap_or(user_sp) as u64, uc_stack)
     };
 
+    // The 32-bit programs take the signal frames in the i386 layout.
+    #[cfg(target_arch = "x86_64")]
+    if user_ctx.is_compat_mode() {
+        return compat::setup_frame(
+            ctx,
+            sig_num,
+            handler_addr,
+            flags,
+            restorer_addr,
+            old_mask,
+            uc_stack,
+            stack_pointer as Vaddr,
+            user_ctx,
+            &sig_info,
+        );
+    }
+
     // To avoid corrupting signal stack, we minus 128 first.
     stack_pointer -= 128;
 
@@ -187,7 +271,8 @@ pub fn handle_user_signal(
     // 2. write ucontext_t.
     stack_pointer = alloc_aligned_in_user_stack(stack_pointer, mem::size_of::<ucontext_t>(), 16)?;
     let mut ucontext = ucontext_t {
-        uc_sigmask: mask.into(),
+        uc_stack,
+        uc_sigmask: old_mask.into(),
         ..Default::default()
     };
     ucontext
@@ -251,31 +336,6 @@ pub fn handle_user_signal(
     Ok(())
 }
 
-/// Use an alternate signal stack, which was installed by sigaltstack.
-/// It the stack is already active, we just increase the handler counter and return None, since
-/// the stack pointer can be read from context.
-/// It the stack is not used by any handler, we will return the new sp in alternate signal stack.
-fn use_alternate_signal_stack(thread_local: &ThreadLocal) -> Option<usize> {
-    let mut sig_stack = thread_local.sig_stack().borrow_mut();
-    let sig_stack = (*sig_stack).as_mut()?;
-
-    if sig_stack.is_disabled() {
-        return None;
-    }
-
-    if sig_stack.is_active() {
-        // The stack is already active, so we just use sp in context.
-        sig_stack.increase_handler_counter();
-        return None;
-    }
-
-    sig_stack.increase_handler_counter();
-
-    // Make sp align at 16. FIXME: is this required?
-    let stack_pointer = (sig_stack.base() + sig_stack.size()).align_down(16);
-    Some(stack_pointer)
-}
-
 fn write_u64_to_user_stack(rsp: u64, value: u64) -> Result<u64> {
     let rsp = rsp - 8;
     current_userspace!().write_val(rsp as Vaddr, &value)?;
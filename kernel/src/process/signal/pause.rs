@@ -8,7 +8,7 @@ use super::sig_mask::SigMask;
 use crate::{
     prelude::*,
     process::posix_thread::AsPosixThread,
-    thread::AsThread,
+    thread::{kernel_thread::AsKernelThread, AsThread},
     time::wait::{ManagedTimeout, TimeoutExt},
 };
 
@@ -23,6 +23,11 @@ use crate::{
 /// which are similar to the `wait`-family methods except that the methods also return
 /// when the waiting thread is interrupted by a POSIX signal.
 /// When this happens, the `pause`-family methods return `Err(EINTR)`.
+///
+/// Kernel threads receive no signals. Instead, the `pause`-family methods return `Err(EINTR)` if
+/// the waiting kernel thread is requested to stop (see [`KernelThread::stop`]).
+///
+/// [`KernelThread::stop`]: crate::thread::kernel_thread::KernelThread::stop
 pub trait Pause: WaitTimeout {
     /// Pauses until the condition is met or a signal interrupts.
     ///
@@ -119,7 +124,28 @@ impl Pause for Waiter {
             .as_ref()
             .and_then(|thread| thread.as_posix_thread())
         else {
-            return self.wait_until_or_timeout_cancelled(cond, || Ok(()), timeout);
+            let Some(kernel_thread) = current_thread
+                .as_ref()
+                .and_then(|thread| thread.as_kernel_thread())
+            else {
+                return self.wait_until_or_timeout_cancelled(cond, || Ok(()), timeout);
+            };
+
+            let cancel_cond = || {
+                if kernel_thread.should_stop() {
+                    return Err(Error::with_message(
+                        Errno::EINTR,
+                        "the current kernel thread is requested to stop",
+                    ));
+                }
+                Ok(())
+            };
+
+            kernel_thread.set_stop_waker(self.waker());
+            let res = self.wait_until_or_timeout_cancelled(cond, cancel_cond, timeout);
+            kernel_thread.clear_stop_waker();
+
+            return res;
         };
 
         let cancel_cond = || {
@@ -156,6 +182,15 @@ impl Pause for Waiter {
             posix_thread.set_signalled_waker(self.waker());
             self.wait();
             posix_thread.clear_signalled_waker();
+        } else if let Some(kernel_thread) = current_thread
+            .as_ref()
+            .and_then(|thread| thread.as_kernel_thread())
+        {
+            kernel_thread.set_stop_waker(self.waker());
+            if !kernel_thread.should_stop() {
+                self.wait();
+            }
+            kernel_thread.clear_stop_waker();
         } else {
             self.wait();
         }
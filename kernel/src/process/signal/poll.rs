@@ -11,7 +11,7 @@ use ostd::{
 };
 
 use crate::{
-    events::{IoEvents, Observer, Subject},
+    events::{EventsFilter, IoEvents, Observer, Subject},
     prelude::*,
 };
 
@@ -37,7 +37,7 @@ const INV_STATE: isize = -1;
 
 struct PolleeInner {
     /// A subject which is monitored with pollers.
-    subject: Subject<IoEvents, IoEvents>,
+    subject: Subject<IoEvents, PollFilter>,
     /// A state that describes how events are cached in the pollee.
     ///
     /// The meaning of this field depends on its value:
@@ -151,9 +151,13 @@ impl Pollee {
     }
 
     fn register_poller(&self, poller: &mut PollHandle, mask: IoEvents) {
+        let filter = PollFilter {
+            mask,
+            is_exclusive: poller.is_exclusive,
+        };
         self.inner
             .subject
-            .register_observer(poller.observer.clone(), mask);
+            .register_observer(poller.observer.clone(), filter);
 
         poller.pollees.push(Arc::downgrade(&self.inner));
     }
@@ -171,6 +175,11 @@ impl Pollee {
         self.inner.subject.notify_observers(&events);
     }
 
+    /// Returns whether there are any pollers registered in the pollee.
+    pub fn has_pollers(&self) -> bool {
+        self.inner.subject.has_observers()
+    }
+
     /// Invalidates the (internal) cached events.
     ///
     /// This method should be called whenever old events disappear but no new events arrive. The
@@ -195,6 +204,8 @@ pub struct PollHandle {
     observer: Weak<dyn Observer<IoEvents>>,
     // The associated pollees.
     pollees: Vec<Weak<PolleeInner>>,
+    // Whether the observer should be notified exclusively.
+    is_exclusive: bool,
 }
 
 impl PollHandle {
@@ -207,9 +218,19 @@ impl PollHandle {
         Self {
             observer,
             pollees: Vec::new(),
+            is_exclusive: false,
         }
     }
 
+    /// Sets whether the observer should be notified exclusively.
+    ///
+    /// When events happen on a pollee, all the non-exclusive observers are notified, but the
+    /// exclusive observers are notified one by one until one of them takes care of the events.
+    /// The setting takes effect on the pollees that are polled with this handle afterwards.
+    pub fn set_exclusive(&mut self, is_exclusive: bool) {
+        self.is_exclusive = is_exclusive;
+    }
+
     /// Resets the handle.
     ///
     /// The observer will be unregistered and will no longer receive events.
@@ -225,6 +246,22 @@ impl PollHandle {
     }
 }
 
+/// The event filter of a poller registered in a [`Pollee`].
+struct PollFilter {
+    mask: IoEvents,
+    is_exclusive: bool,
+}
+
+impl EventsFilter<IoEvents> for PollFilter {
+    fn filter(&self, events: &IoEvents) -> bool {
+        self.mask.filter(events)
+    }
+
+    fn is_exclusive(&self) -> bool {
+        self.is_exclusive
+    }
+}
+
 impl Drop for PollHandle {
     fn drop(&mut self) {
         self.reset();
@@ -28,7 +28,10 @@ impl TryFrom<sigaction_t> for SigAction {
             SIG_IGN => SigAction::Ign,
             _ => {
                 let flags = SigActionFlags::from_bits_truncate(input.flags);
-                let mask = input.mask.into();
+                let mut mask: SigMask = input.mask.into();
+                // Like `sigprocmask`, `SIGKILL` and `SIGSTOP` cannot be blocked by the handler.
+                mask -= SIGKILL;
+                mask -= SIGSTOP;
                 SigAction::User {
                     handler_addr: input.handler_ptr,
                     flags,
@@ -100,7 +103,7 @@ impl SigActionFlags {
     }
 
     pub fn contains_unsupported_flag(&self) -> bool {
-        self.intersects(SigActionFlags::SA_NOCLDSTOP | SigActionFlags::SA_NOCLDWAIT)
+        self.intersects(SigActionFlags::SA_NOCLDWAIT)
     }
 }
 
@@ -1,5 +1,6 @@
 // SPDX-License-Identifier: MPL-2.0
 
+use super::c_types::stack_t;
 use crate::prelude::*;
 
 /// User-provided signal stack. `SigStack` is per-thread, and each thread can have
@@ -7,13 +8,14 @@ use crate::prelude::*;
 /// the handler should be executed on the `SigStack`, instead of on the default stack.
 ///
 /// SigStack can be registered and unregistered by syscall `sigaltstack`.
+///
+/// Like Linux, whether a handler is running on the stack is determined by the stack pointer,
+/// so that the stack can be used again after the handler exits by `siglongjmp`.
 #[derive(Debug, Clone)]
 pub struct SigStack {
     base: Vaddr,
     flags: SigStackFlags,
     size: usize,
-    /// The number of handlers that are currently using the stack
-    handler_counter: usize,
 }
 
 bitflags! {
@@ -24,26 +26,9 @@ bitflags! {
     }
 }
 
-#[repr(u8)]
-#[allow(non_camel_case_types)]
-#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
-pub enum SigStackStatus {
-    #[default]
-    SS_INACTIVE = 0,
-    // The thread is currently executing on the alternate signal stack
-    SS_ONSTACK = 1,
-    // The stack is currently disabled.
-    SS_DISABLE = 2,
-}
-
 impl SigStack {
     pub fn new(base: Vaddr, flags: SigStackFlags, size: usize) -> Self {
-        Self {
-            base,
-            flags,
-            size,
-            handler_counter: 0,
-        }
+        Self { base, flags, size }
     }
 
     pub fn base(&self) -> Vaddr {
@@ -58,42 +43,98 @@ impl SigStack {
         self.size
     }
 
-    pub fn status(&self) -> SigStackStatus {
-        // Learning From [sigaltstack doc](https://man7.org/linux/man-pages/man2/sigaltstack.2.html):
-        // If the stack is currently executed on,
-        // 1. If the stack was established with flag SS_AUTODISARM, the stack status is DISABLE,
-        // 2. otherwise, the stack status is ONSTACK
-        if self.handler_counter == 0 {
-            if self.flags.contains(SigStackFlags::SS_AUTODISARM) {
-                SigStackStatus::SS_DISABLE
-            } else {
-                SigStackStatus::SS_INACTIVE
-            }
-        } else {
-            SigStackStatus::SS_ONSTACK
+    /// Returns whether the stack is disabled.
+    pub fn is_disabled(&self) -> bool {
+        self.size == 0
+    }
+
+    /// Returns whether the stack pointer is on the stack.
+    pub fn is_on_stack(&self, sp: Vaddr) -> bool {
+        // With `SS_AUTODISARM`, the stack is disabled while a handler is running on it. If the
+        // stack pointer happens to be on it, the stack must have been re-enabled by the handler,
+        // so Linux thinks that it is not on the stack.
+        if self.flags.contains(SigStackFlags::SS_AUTODISARM) {
+            return false;
         }
+
+        // The stack grows downwards, so the top of the stack is not a valid stack pointer.
+        sp > self.base && sp - self.base <= self.size
     }
 
-    /// Mark the stack is currently used by a signal handler.    
-    pub fn increase_handler_counter(&mut self) {
-        self.handler_counter += 1;
+    /// Returns the stack pointer to run a signal handler on the stack.
+    ///
+    /// `sp` is the stack pointer of the interrupted user code. If the stack is disabled or
+    /// already in use, this method returns `None`.
+    pub fn handler_stack_pointer(&self, sp: Vaddr) -> Option<Vaddr> {
+        if self.is_disabled() || self.is_on_stack(sp) {
+            return None;
+        }
+
+        Some(self.base + self.size)
+    }
+
+    /// Converts the stack to the C type.
+    ///
+    /// The status flags (i.e., `SS_ONSTACK` or `SS_DISABLE`) are determined by the stack
+    /// pointer `sp`, as in the result of `sigaltstack`.
+    pub fn as_c_type(&self, sp: Vaddr) -> stack_t {
+        let status = if self.is_disabled() {
+            SigStackFlags::SS_DISABLE
+        } else if self.is_on_stack(sp) {
+            SigStackFlags::SS_ONSTACK
+        } else {
+            SigStackFlags::empty()
+        };
+        let flags = status | (self.flags & SigStackFlags::SS_AUTODISARM);
+
+        stack_t {
+            ss_sp: self.base,
+            ss_flags: flags.bits() as i32,
+            ss_size: self.size,
+        }
     }
 
-    // Mark the stack is freed by current handler.
-    pub fn decrease_handler_counter(&mut self) {
-        // FIXME: deal with SS_AUTODISARM flag
-        self.handler_counter -= 1
+    /// Converts the stack to the C type that is saved in the signal frame.
+    ///
+    /// Unlike [`Self::as_c_type`], the flags are kept unchanged, so that the stack can be
+    /// restored when the handler returns.
+    pub fn as_saved_c_type(&self) -> stack_t {
+        stack_t {
+            ss_sp: self.base,
+            ss_flags: self.flags.bits() as i32,
+            ss_size: self.size,
+        }
     }
+}
 
-    /// Determines whether the stack is executed on by any signal handler
-    pub fn is_active(&self) -> bool {
-        (self.handler_counter > 0)
-            && !(self.flags.intersects(SigStackFlags::SS_AUTODISARM)
-                || self.flags.intersects(SigStackFlags::SS_DISABLE))
+impl Default for SigStack {
+    fn default() -> Self {
+        Self::new(0, SigStackFlags::SS_DISABLE, 0)
     }
+}
 
-    pub fn is_disabled(&self) -> bool {
-        self.flags.contains(SigStackFlags::SS_DISABLE)
-            || (self.handler_counter > 0 && self.flags.contains(SigStackFlags::SS_AUTODISARM))
+impl TryFrom<stack_t> for SigStack {
+    type Error = Error;
+
+    fn try_from(stack: stack_t) -> Result<Self> {
+        let flags = SigStackFlags::from_bits(stack.ss_flags as u32)
+            .ok_or_else(|| Error::with_message(Errno::EINVAL, "invalid flags"))?;
+        if flags.contains(SigStackFlags::SS_ONSTACK | SigStackFlags::SS_DISABLE) {
+            return_errno_with_message!(Errno::EINVAL, "invalid flags");
+        }
+
+        if flags.contains(SigStackFlags::SS_DISABLE) {
+            return Ok(Self::default());
+        }
+        if stack.ss_size < MINSTKSZ {
+            return_errno_with_message!(Errno::ENOMEM, "stack size is less than MINSTKSZ");
+        }
+        if stack.ss_sp.checked_add(stack.ss_size).is_none() {
+            return_errno_with_message!(Errno::EINVAL, "overflow for given stack addr and size");
+        }
+
+        Ok(Self::new(stack.ss_sp, flags, stack.ss_size))
     }
 }
+
+const MINSTKSZ: usize = 2048;
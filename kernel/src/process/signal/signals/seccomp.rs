@@ -0,0 +1,44 @@
+// SPDX-License-Identifier: MPL-2.0
+
+use super::Signal;
+use crate::{
+    prelude::*,
+    process::signal::{
+        c_types::siginfo_t,
+        constants::{SIGSYS, SYS_SECCOMP},
+        sig_num::SigNum,
+    },
+};
+
+/// The `SIGSYS` signal that is sent when a seccomp filter traps a system call.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SeccompSignal {
+    call_addr: Vaddr,
+    syscall: i32,
+    arch: u32,
+    errno: i32,
+}
+
+impl SeccompSignal {
+    pub fn new(call_addr: Vaddr, syscall: i32, arch: u32, errno: i32) -> Self {
+        Self {
+            call_addr,
+            syscall,
+            arch,
+            errno,
+        }
+    }
+}
+
+impl Signal for SeccompSignal {
+    fn num(&self) -> SigNum {
+        SIGSYS
+    }
+
+    fn to_info(&self) -> siginfo_t {
+        let mut info = siginfo_t::new(SIGSYS, SYS_SECCOMP);
+        info.si_errno = self.errno;
+        info.set_si_sigsys(self.call_addr, self.syscall, self.arch);
+        info
+    }
+}
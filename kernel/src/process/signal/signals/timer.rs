@@ -0,0 +1,114 @@
+// SPDX-License-Identifier: MPL-2.0
+
+use alloc::sync::Arc;
+use core::fmt::Debug;
+
+use ostd::sync::{LocalIrqDisabled, SpinLock};
+
+use super::Signal;
+use crate::process::signal::{
+    c_types::{siginfo_t, sigval_t},
+    constants::SI_TIMER,
+    sig_num::SigNum,
+};
+
+/// A signal sent by a POSIX timer upon expiration.
+///
+/// Like Linux, at most one signal of a timer is pending at a time. If the timer expires while
+/// the signal is pending, the expiration is counted as an overrun, which is reported to the
+/// receiver of the signal and by `timer_getoverrun()`.
+pub struct TimerSignal {
+    num: SigNum,
+    timer_id: i32,
+    value: sigval_t,
+    overrun: Arc<TimerOverrun>,
+}
+
+impl TimerSignal {
+    pub fn new(num: SigNum, timer_id: i32, value: sigval_t, overrun: Arc<TimerOverrun>) -> Self {
+        Self {
+            num,
+            timer_id,
+            value,
+            overrun,
+        }
+    }
+}
+
+impl Debug for TimerSignal {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("TimerSignal")
+            .field("num", &self.num)
+            .field("timer_id", &self.timer_id)
+            .finish_non_exhaustive()
+    }
+}
+
+impl Signal for TimerSignal {
+    fn num(&self) -> SigNum {
+        self.num
+    }
+
+    fn to_info(&self) -> siginfo_t {
+        let mut info = siginfo_t::new(self.num, SI_TIMER);
+        info.set_si_timer(self.timer_id, self.overrun.take(), self.value);
+        info
+    }
+}
+
+impl Drop for TimerSignal {
+    fn drop(&mut self) {
+        // The signal is delivered, ignored, or discarded, so the timer can send a new one.
+        self.overrun.inner.lock().is_pending = false;
+    }
+}
+
+/// The overrun count of a POSIX timer.
+#[derive(Debug, Default)]
+pub struct TimerOverrun {
+    inner: SpinLock<TimerOverrunInner, LocalIrqDisabled>,
+}
+
+#[derive(Debug, Default)]
+struct TimerOverrunInner {
+    /// Whether a signal of the timer is pending.
+    is_pending: bool,
+    /// The number of expirations since the pending signal is sent.
+    overrun: i32,
+    /// The overrun count of the last delivered signal.
+    last_overrun: i32,
+}
+
+impl TimerOverrun {
+    /// Records an expiration of the timer.
+    ///
+    /// Returns whether a new signal should be sent, i.e., no signal of the timer is pending.
+    pub fn expire(&self) -> bool {
+        let mut inner = self.inner.lock();
+        if inner.is_pending {
+            inner.overrun = inner.overrun.saturating_add(1);
+            return false;
+        }
+        inner.is_pending = true;
+        true
+    }
+
+    /// Returns the overrun count of the last delivered signal.
+    pub fn last(&self) -> i32 {
+        self.inner.lock().last_overrun
+    }
+
+    /// Resets the overrun count when the timer is armed again.
+    pub fn reset(&self) {
+        let mut inner = self.inner.lock();
+        inner.overrun = 0;
+        inner.last_overrun = 0;
+    }
+
+    /// Takes the overrun count when the pending signal is delivered.
+    fn take(&self) -> i32 {
+        let mut inner = self.inner.lock();
+        inner.last_overrun = core::mem::take(&mut inner.overrun);
+        inner.last_overrun
+    }
+}
@@ -2,14 +2,19 @@
 
 #![allow(dead_code)]
 
+use core::fmt::Debug;
+
 use super::Signal;
-use crate::process::{
-    signal::{
-        c_types::siginfo_t,
-        constants::{SI_QUEUE, SI_TKILL, SI_USER},
-        sig_num::SigNum,
+use crate::{
+    current,
+    process::{
+        signal::{
+            c_types::siginfo_t,
+            constants::{SI_QUEUE, SI_TKILL, SI_USER},
+            sig_num::SigNum,
+        },
+        Pid, Uid,
     },
-    Pid, Uid,
 };
 
 #[derive(Debug, Clone, Copy)]
@@ -58,11 +63,58 @@ impl Signal for UserSignal {
             UserSignalKind::Sigqueue => SI_QUEUE,
         };
 
-        siginfo_t::new(self.num, code)
-        // info.set_si_pid(self.pid);
-        // info.set_si_uid(self.uid);
+        let mut info = siginfo_t::new(self.num, code);
+        // The information is retrieved by the receiver, which may be in a different PID
+        // namespace from the sender.
+        let pid = current!().pid_ns().local_id(self.pid).unwrap_or(0);
+        info.set_si_pid_uid(pid, self.uid);
         // if let UserSignalKind::Sigqueue(val) = self.kind {
         //     info.set_si_value(val);
         // }
+        info
+    }
+}
+
+/// A signal with the information provided by the sender (e.g., via `rt_sigqueueinfo`).
+#[derive(Clone, Copy)]
+pub struct UserInfoSignal {
+    num: SigNum,
+    info: siginfo_t,
+}
+
+impl UserInfoSignal {
+    /// Creates a signal with the information provided by the sender.
+    ///
+    /// The signal number in `info` is ignored and replaced with `num`.
+    pub fn new(num: SigNum, mut info: siginfo_t) -> Self {
+        info.si_signo = num.as_u8() as i32;
+        Self { num, info }
+    }
+
+    /// Returns whether the information pretends that the signal is sent by the kernel or by
+    /// `kill`/`tgkill`.
+    ///
+    /// Like Linux, such information can only be used if a process sends the signal to itself.
+    pub fn is_impersonating(&self) -> bool {
+        self.info.si_code >= 0 || self.info.si_code == SI_TKILL
+    }
+}
+
+impl Debug for UserInfoSignal {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("UserInfoSignal")
+            .field("num", &self.num)
+            .field("code", &self.info.si_code)
+            .finish_non_exhaustive()
+    }
+}
+
+impl Signal for UserInfoSignal {
+    fn num(&self) -> SigNum {
+        self.num
+    }
+
+    fn to_info(&self) -> siginfo_t {
+        self.info
     }
 }
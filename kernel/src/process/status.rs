@@ -4,17 +4,31 @@
 
 use core::sync::atomic::{AtomicBool, AtomicU32, Ordering};
 
-use super::ExitCode;
+use super::{signal::sig_num::SigNum, wait::WaitOptions, ExitCode};
+use crate::prelude::*;
 
 /// The status of a process.
 ///
 /// This maintains:
 /// 1. Whether the process is a zombie (i.e., all its threads have exited);
-/// 2. The exit code of the process.
+/// 2. The exit code of the process;
+/// 3. Whether the process is stopped by a stop signal, and the job control status that has not
+///    been reported to the parent.
 #[derive(Debug)]
 pub struct ProcessStatus {
     is_zombie: AtomicBool,
     exit_code: AtomicU32,
+    is_stopped: AtomicBool,
+    job_status: SpinLock<Option<JobStatus>>,
+}
+
+/// A change of the job control status of a process.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum JobStatus {
+    /// The process has been stopped by the signal.
+    Stopped(SigNum),
+    /// The process has been continued by `SIGCONT`.
+    Continued,
 }
 
 impl Default for ProcessStatus {
@@ -22,6 +36,8 @@ impl Default for ProcessStatus {
         Self {
             is_zombie: AtomicBool::new(false),
             exit_code: AtomicU32::new(0),
+            is_stopped: AtomicBool::new(false),
+            job_status: SpinLock::new(None),
         }
     }
 }
@@ -55,3 +71,47 @@ impl ProcessStatus {
         self.exit_code.store(exit_code, Ordering::Relaxed);
     }
 }
+
+impl ProcessStatus {
+    /// Sets the process to be stopped by the signal.
+    pub(super) fn set_stopped(&self, sig_num: SigNum) {
+        let mut job_status = self.job_status.lock();
+        self.is_stopped.store(true, Ordering::Relaxed);
+        *job_status = Some(JobStatus::Stopped(sig_num));
+    }
+
+    /// Sets the process to be continued.
+    ///
+    /// This method returns `false` if the process is not stopped.
+    pub(super) fn set_continued(&self) -> bool {
+        let mut job_status = self.job_status.lock();
+        if !self.is_stopped.swap(false, Ordering::Relaxed) {
+            return false;
+        }
+        *job_status = Some(JobStatus::Continued);
+        true
+    }
+
+    /// Takes the job control status that has not been reported to the parent.
+    ///
+    /// Only the status changes requested by `wait_options` (i.e., `WaitOptions::WSTOPPED` and
+    /// `WaitOptions::WCONTINUED`) are taken. The status is kept if `WaitOptions::WNOWAIT` is
+    /// specified.
+    pub(super) fn take_job_status(&self, wait_options: WaitOptions) -> Option<JobStatus> {
+        let mut job_status = self.job_status.lock();
+
+        let status = (*job_status)?;
+        let is_wanted = match status {
+            JobStatus::Stopped(_) => wait_options.contains(WaitOptions::WSTOPPED),
+            JobStatus::Continued => wait_options.contains(WaitOptions::WCONTINUED),
+        };
+        if !is_wanted {
+            return None;
+        }
+
+        if !wait_options.contains(WaitOptions::WNOWAIT) {
+            *job_status = None;
+        }
+        Some(status)
+    }
+}
@@ -62,11 +62,40 @@ impl TaskSet {
         self.has_exited_main && self.tasks.len() == 1
     }
 
+    /// Makes the task the main task, replacing the exited main task.
+    ///
+    /// This should only be called by `execve` after the other threads have been killed, so that
+    /// the task that calls `execve` takes over the task slot of the main thread.
+    ///
+    /// # Panics
+    ///
+    /// This method will panic if the task is not in the task set or the main task has not exited.
+    pub(super) fn replace_main(&mut self, task: &Task) {
+        assert!(self.has_exited_main);
+
+        let position = self
+            .tasks
+            .iter()
+            .position(|some_task| core::ptr::eq(some_task.as_ref(), task))
+            .unwrap();
+        self.tasks.swap(0, position);
+        self.tasks.swap_remove(position);
+        self.has_exited_main = false;
+    }
+
     /// Sets a flag that denotes that an `exit_group` has been initiated.
     pub(super) fn set_exited_group(&mut self) {
         self.has_exited_group = true;
     }
 
+    /// Clears the flag set by [`Self::set_exited_group`].
+    ///
+    /// This should only be called by `execve` after the other threads have been killed, so that
+    /// the new program can create threads.
+    pub(super) fn clear_exited_group(&mut self) {
+        self.has_exited_group = false;
+    }
+
     /// Returns whether an `exit_group` has been initiated.
     pub(super) fn has_exited_group(&self) -> bool {
         self.has_exited_group
@@ -6,14 +6,20 @@ use super::signal::sig_num::SigNum;
 pub enum TermStatus {
     Exited(u8),
     Killed(SigNum),
+    /// Killed by the signal after the process is dumped into a core file.
+    CoreDumped(SigNum),
 }
 
+/// The flag in the wait status indicating that a core file is produced (`WCOREFLAG`).
+const CORE_DUMP_FLAG: u32 = 0x80;
+
 impl TermStatus {
     /// Return as a 32-bit integer encoded as specified in wait(2) man page.
     pub fn as_u32(&self) -> u32 {
         match self {
             TermStatus::Exited(status) => (*status as u32) << 8,
             TermStatus::Killed(signum) => signum.as_u8() as u32,
+            TermStatus::CoreDumped(signum) => signum.as_u8() as u32 | CORE_DUMP_FLAG,
         }
     }
 }
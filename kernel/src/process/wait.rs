@@ -2,35 +2,98 @@
 
 #![allow(dead_code)]
 
-use super::{process_filter::ProcessFilter, signal::constants::SIGCHLD, ExitCode, Pid, Process};
+use super::{
+    process_filter::ProcessFilter,
+    signal::{constants::SIGCHLD, sig_num::SigNum},
+    status::JobStatus,
+    ExitCode, Pid, Process, Uid,
+};
 use crate::{
+    events::IoEvents,
     prelude::*,
     process::{
         posix_thread::{thread_table, AsPosixThread},
         process_table,
         signal::with_signal_blocked,
     },
+    thread::Thread,
+    time::clocks::ProfClock,
 };
 
 // The definition of WaitOptions is from Occlum
 bitflags! {
     pub struct WaitOptions: u32 {
         const WNOHANG = 0x1;
-        //Note: Below flags are not supported yet
         const WSTOPPED = 0x2; // Same as WUNTRACED
         const WEXITED = 0x4;
         const WCONTINUED = 0x8;
         const WNOWAIT = 0x01000000;
+        //Note: Below flags are not supported yet
         const WNOTHREAD = 0x20000000;
         const WALL = 0x40000000;
         const WCLONE = 0x80000000;
     }
 }
 
-impl WaitOptions {
-    pub fn supported(&self) -> bool {
-        let unsupported_flags = WaitOptions::all() - WaitOptions::WNOHANG;
-        !self.intersects(unsupported_flags)
+/// A child that has been waited for.
+pub enum WaitedChild {
+    /// A child process that has exited.
+    ///
+    /// The process has been reaped, unless `WaitOptions::WNOWAIT` is specified.
+    Zombie(Arc<Process>),
+    /// A child process that has been stopped by the signal.
+    Stopped(Arc<Process>, SigNum),
+    /// A child process that has been continued by `SIGCONT`.
+    Continued(Arc<Process>),
+    /// A tracee that is in a ptrace-stop, with the wait status.
+    PtraceStopped(Arc<Thread>, u32),
+}
+
+impl WaitedChild {
+    /// Returns the global ID of the process or the thread.
+    pub fn id(&self) -> Pid {
+        match self {
+            Self::Zombie(process) | Self::Stopped(process, _) | Self::Continued(process) => {
+                process.pid()
+            }
+            Self::PtraceStopped(thread, _) => thread.as_posix_thread().unwrap().tid(),
+        }
+    }
+
+    /// Returns the wait status.
+    pub fn status(&self) -> u32 {
+        match self {
+            Self::Zombie(process) => process.status().exit_code(),
+            Self::Stopped(_, sig_num) => ((sig_num.as_u8() as u32) << 8) | 0x7f,
+            Self::Continued(_) => 0xffff,
+            Self::PtraceStopped(_, status) => *status,
+        }
+    }
+
+    /// Returns the real user ID of the process or the thread.
+    pub fn uid(&self) -> Uid {
+        let thread = match self {
+            Self::Zombie(process) | Self::Stopped(process, _) | Self::Continued(process) => {
+                process.main_thread()
+            }
+            Self::PtraceStopped(thread, _) => thread.clone(),
+        };
+        thread.as_posix_thread().unwrap().credentials().ruid()
+    }
+
+    /// Returns the profiling clock of the process.
+    pub fn prof_clock(&self) -> Arc<ProfClock> {
+        match self {
+            Self::Zombie(process) | Self::Stopped(process, _) | Self::Continued(process) => {
+                process.prof_clock().clone()
+            }
+            Self::PtraceStopped(thread, _) => thread
+                .as_posix_thread()
+                .unwrap()
+                .process()
+                .prof_clock()
+                .clone(),
+        }
     }
 }
 
@@ -38,9 +101,9 @@ pub fn wait_child_exit(
     child_filter: ProcessFilter,
     wait_options: WaitOptions,
     ctx: &Context,
-) -> Result<Option<Arc<Process>>> {
+) -> Result<Option<WaitedChild>> {
     let current = ctx.process;
-    let zombie_child = with_signal_blocked(ctx, SIGCHLD.into(), || {
+    let waited_child = with_signal_blocked(ctx, SIGCHLD.into(), || {
         current.children_wait_queue().pause_until(|| {
             let unwaited_children = current
                 .children()
@@ -54,7 +117,22 @@ pub fn wait_child_exit(
                 .cloned()
                 .collect::<Vec<_>>();
 
-            if unwaited_children.is_empty() {
+            let tracees = current
+                .tracees()
+                .lock()
+                .values()
+                .filter(|tracee| {
+                    let posix_thread = tracee.as_posix_thread().unwrap();
+                    match child_filter {
+                        ProcessFilter::Any => true,
+                        ProcessFilter::WithPid(pid) => posix_thread.tid() == pid,
+                        ProcessFilter::WithPgid(pgid) => posix_thread.process().pgid() == pgid,
+                    }
+                })
+                .cloned()
+                .collect::<Vec<_>>();
+
+            if unwaited_children.is_empty() && tracees.is_empty() {
                 return Some(Err(Error::with_message(
                     Errno::ECHILD,
                     "the process has no child to wait",
@@ -62,18 +140,41 @@ pub fn wait_child_exit(
             }
 
             // return immediately if we find a zombie child
-            let zombie_child = unwaited_children
-                .iter()
-                .find(|child| child.status().is_zombie());
+            let zombie_child = unwaited_children.iter().find(|child| {
+                wait_options.contains(WaitOptions::WEXITED) && child.status().is_zombie()
+            });
 
             if let Some(zombie_child) = zombie_child {
                 let zombie_pid = zombie_child.pid();
                 if wait_options.contains(WaitOptions::WNOWAIT) {
                     // does not reap child, directly return
-                    return Some(Ok(Some(zombie_child.clone())));
+                    return Some(Ok(Some(WaitedChild::Zombie(zombie_child.clone()))));
                 } else {
                     reap_zombie_child(current, zombie_pid);
-                    return Some(Ok(Some(zombie_child.clone())));
+                    return Some(Ok(Some(WaitedChild::Zombie(zombie_child.clone()))));
+                }
+            }
+
+            let job_status = unwaited_children.iter().find_map(|child| {
+                let status = child.status().take_job_status(wait_options)?;
+                Some((child, status))
+            });
+            match job_status {
+                Some((child, JobStatus::Stopped(sig_num))) => {
+                    return Some(Ok(Some(WaitedChild::Stopped(child.clone(), sig_num))));
+                }
+                Some((child, JobStatus::Continued)) => {
+                    return Some(Ok(Some(WaitedChild::Continued(child.clone()))));
+                }
+                None => {}
+            }
+
+            // Ptrace-stops are reported regardless of `WaitOptions::WSTOPPED`.
+            let should_consume = !wait_options.contains(WaitOptions::WNOWAIT);
+            for tracee in tracees {
+                let ptrace = tracee.as_posix_thread().unwrap().ptrace();
+                if let Some(status) = ptrace.report_stop(current, should_consume) {
+                    return Some(Ok(Some(WaitedChild::PtraceStopped(tracee, status))));
                 }
             }
 
@@ -86,7 +187,7 @@ pub fn wait_child_exit(
         })
     })??;
 
-    Ok(zombie_child)
+    Ok(waited_child)
 }
 
 /// Free zombie child with pid, returns the exit code of child process.
@@ -124,5 +225,6 @@ fn reap_zombie_child(process: &Process, pid: Pid) -> ExitCode {
     }
 
     process_table_mut.remove(child_process.pid());
+    child_process.pidfd_pollee().notify(IoEvents::HUP);
     child_process.status().exit_code()
 }
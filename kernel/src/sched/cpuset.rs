@@ -0,0 +1,116 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! Cpusets, which confine groups of processes to subsets of the CPUs.
+//!
+//! Every process belongs to exactly one cpuset, and the CPU affinities of its threads are always
+//! subsets of the CPUs of that cpuset. The cpuset of a process is determined by its cgroup, and
+//! the processes in the root cgroup belong to [`Cpuset::root`], which contains all the CPUs.
+
+use core::fmt::{self, Write};
+
+use ostd::cpu::{CpuId, CpuSet};
+use spin::Once;
+
+use crate::prelude::*;
+
+/// A group of processes that can only run on a subset of the CPUs.
+pub struct Cpuset {
+    cpus: Mutex<CpuSet>,
+}
+
+impl Cpuset {
+    /// Returns the root cpuset, which contains all the CPUs.
+    pub fn root() -> &'static Arc<Cpuset> {
+        static ROOT: Once<Arc<Cpuset>> = Once::new();
+
+        ROOT.call_once(|| Self::new(CpuSet::new_full()))
+    }
+
+    /// Creates a cpuset with the CPUs.
+    pub fn new(cpus: CpuSet) -> Arc<Self> {
+        Arc::new(Self {
+            cpus: Mutex::new(cpus),
+        })
+    }
+
+    /// Returns the CPUs of the cpuset.
+    pub fn cpus(&self) -> CpuSet {
+        self.cpus.lock().clone()
+    }
+
+    /// Sets the CPUs of the cpuset.
+    ///
+    /// The caller is responsible for restricting the CPU affinities of the threads in the cpuset.
+    pub fn set_cpus(&self, cpus: CpuSet) {
+        *self.cpus.lock() = cpus;
+    }
+}
+
+/// Returns the CPUs that are in both sets.
+pub(crate) fn intersect(a: &CpuSet, b: &CpuSet) -> CpuSet {
+    let mut result = CpuSet::new_empty();
+    for cpu in a.iter().filter(|cpu| b.contains(*cpu)) {
+        result.add(cpu);
+    }
+    result
+}
+
+/// Formats a CPU set as a list of ranges, e.g., `0-3,5`.
+pub struct CpuList<'a>(pub &'a CpuSet);
+
+impl fmt::Display for CpuList<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut ranges: Vec<(usize, usize)> = Vec::new();
+        for id in self.0.iter().map(|cpu| cpu.as_usize()) {
+            match ranges.last_mut() {
+                Some((_, end)) if *end + 1 == id => *end = id,
+                _ => ranges.push((id, id)),
+            }
+        }
+
+        for (i, (start, end)) in ranges.into_iter().enumerate() {
+            if i > 0 {
+                f.write_char(',')?;
+            }
+            if start == end {
+                write!(f, "{}", start)?;
+            } else {
+                write!(f, "{}-{}", start, end)?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Parses a list of ranges of CPUs, e.g., `0-3,5`.
+pub fn parse_cpu_list(list: &str) -> Result<CpuSet> {
+    let mut cpus = CpuSet::new_empty();
+
+    let parse_id = |id: &str| {
+        id.trim()
+            .parse::<usize>()
+            .ok()
+            .and_then(|id| CpuId::try_from(id).ok())
+            .ok_or_else(|| Error::with_message(Errno::EINVAL, "the CPU ID is invalid"))
+    };
+
+    for range in list.split(',').filter(|range| !range.trim().is_empty()) {
+        let (start, end) = match range.split_once('-') {
+            Some((start, end)) => (parse_id(start)?, parse_id(end)?),
+            None => {
+                let id = parse_id(range)?;
+                (id, id)
+            }
+        };
+        if start.as_usize() > end.as_usize() {
+            return_errno_with_message!(Errno::EINVAL, "the range of CPUs is invalid");
+        }
+
+        for id in start.as_usize()..=end.as_usize() {
+            cpus.add(CpuId::try_from(id).unwrap());
+        }
+    }
+
+    Ok(cpus)
+}
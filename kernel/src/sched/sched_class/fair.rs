@@ -1,6 +1,6 @@
 // SPDX-License-Identifier: MPL-2.0
 
-use alloc::{collections::binary_heap::BinaryHeap, sync::Arc};
+use alloc::{collections::binary_heap::BinaryHeap, sync::Arc, vec::Vec};
 use core::{
     cmp::{self, Reverse},
     sync::atomic::{AtomicU64, Ordering::*},
@@ -15,7 +15,8 @@ use ostd::{
 };
 
 use super::{
-    time::{base_slice_clocks, min_period_clocks},
+    sched_clock,
+    time::{base_slice_clocks, clocks_to_ns, min_period_clocks},
     CurrentRuntime, SchedAttr, SchedClassRq,
 };
 use crate::{
@@ -24,6 +25,9 @@ use crate::{
 };
 
 const WEIGHT_0: u64 = 1024;
+/// The weight of the threads with the highest nice value.
+pub(super) const MAX_WEIGHT: u64 = nice_to_weight(Nice::new(NiceRange::new(NiceRange::MIN)));
+
 pub const fn nice_to_weight(nice: Nice) -> u64 {
     // Calculated by the formula below:
     //
@@ -111,11 +115,13 @@ impl FairAttr {
         self.weight.store(nice_to_weight(nice), Relaxed);
     }
 
-    fn update_vruntime(&self, delta: u64) -> (u64, u64) {
-        let weight = self.weight.load(Relaxed);
+    pub(super) fn weight(&self) -> u64 {
+        self.weight.load(Relaxed)
+    }
+
+    fn update_vruntime(&self, delta: u64, weight: u64) -> u64 {
         let delta = delta * WEIGHT_0 / weight;
-        let vruntime = self.vruntime.fetch_add(delta, Relaxed) + delta;
-        (vruntime, weight)
+        self.vruntime.fetch_add(delta, Relaxed) + delta
     }
 }
 
@@ -123,7 +129,11 @@ impl FairAttr {
 ///
 /// This structure is used to provide the capability for keying in the
 /// run queue implemented by `BTreeSet` in the `FairClassRq`.
-struct FairQueueItem(Arc<Task>, u64);
+///
+/// The weight at the time of enqueuing is also recorded, since the nice
+/// value or the group of the thread may be changed while the thread is in
+/// the run queue.
+struct FairQueueItem(Arc<Task>, u64, u64);
 
 impl core::fmt::Debug for FairQueueItem {
     fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
@@ -169,6 +179,10 @@ pub(super) struct FairClassRq {
     cpu: CpuId,
     /// The ready-to-run threads.
     entities: BinaryHeap<Reverse<FairQueueItem>>,
+    /// The threads whose groups have used up their CPU bandwidth.
+    ///
+    /// They are not counted in the length and the total weight of the run queue.
+    throttled: Vec<FairQueueItem>,
     /// The minimum of vruntime in the run queue. Serves as the initial
     /// value of newly-enqueued threads.
     min_vruntime: u64,
@@ -180,6 +194,7 @@ impl FairClassRq {
         Self {
             cpu,
             entities: BinaryHeap::new(),
+            throttled: Vec::new(),
             min_vruntime: 0,
             total_weight: 0,
         }
@@ -217,11 +232,52 @@ impl FairClassRq {
     fn time_slice(&self, cur_weight: u64) -> u64 {
         self.period() * cur_weight / (self.total_weight + cur_weight)
     }
+
+    /// Returns the sum of the weights of the threads in the run queue.
+    pub(super) fn total_weight(&self) -> u64 {
+        self.total_weight
+    }
+
+    /// Returns whether a thread that has just been enqueued should preempt the current thread.
+    ///
+    /// Like Linux's wakeup preemption, the current thread is preempted only if it has run for a
+    /// base time slice longer than the new thread, measured in vruntime clocks. This prevents
+    /// frequent wakeups from causing excessive context switches.
+    pub(super) fn should_preempt(&self, new_attr: &SchedAttr, cur_attr: &SchedAttr) -> bool {
+        let new_vruntime = new_attr.fair.vruntime.load(Relaxed);
+        let cur_vruntime = cur_attr.fair.vruntime.load(Relaxed);
+
+        new_vruntime + base_slice_clocks() < cur_vruntime
+    }
+
+    /// Moves the throttled threads back to the run queue if their groups can run again.
+    pub(super) fn unthrottle(&mut self, now: u64) {
+        let mut i = 0;
+        while i < self.throttled.len() {
+            if is_throttled(&self.throttled[i].0, now) {
+                i += 1;
+                continue;
+            }
+
+            let item = self.throttled.swap_remove(i);
+            self.total_weight += item.2;
+            self.entities.push(Reverse(item));
+        }
+    }
+}
+
+fn is_throttled(task: &Task, now: u64) -> bool {
+    task.as_thread()
+        .unwrap()
+        .sched_attr()
+        .group()
+        .is_throttled(now)
 }
 
 impl SchedClassRq for FairClassRq {
     fn enqueue(&mut self, entity: Arc<Task>, flags: Option<EnqueueFlags>) {
-        let fair_attr = &entity.as_thread().unwrap().sched_attr().fair;
+        let attr = entity.as_thread().unwrap().sched_attr();
+        let fair_attr = &attr.fair;
         let vruntime = match flags {
             Some(EnqueueFlags::Spawn) => self.min_vruntime + self.vtime_slice(),
             _ => self.min_vruntime,
@@ -231,8 +287,10 @@ impl SchedClassRq for FairClassRq {
             .fetch_max(vruntime, Relaxed)
             .max(vruntime);
 
-        self.total_weight += fair_attr.weight.load(Relaxed);
-        self.entities.push(Reverse(FairQueueItem(entity, vruntime)));
+        let weight = attr.fair_weight();
+        self.total_weight += weight;
+        self.entities
+            .push(Reverse(FairQueueItem(entity, vruntime, weight)));
     }
 
     fn len(&mut self) -> usize {
@@ -244,12 +302,19 @@ impl SchedClassRq for FairClassRq {
     }
 
     fn pick_next(&mut self) -> Option<Arc<Task>> {
-        let Reverse(FairQueueItem(entity, _)) = self.entities.pop()?;
+        let now = clocks_to_ns(sched_clock());
 
-        let sched_attr = entity.as_thread().unwrap().sched_attr();
-        self.total_weight -= sched_attr.fair.weight.load(Relaxed);
+        loop {
+            let Reverse(item) = self.entities.pop()?;
+            self.total_weight -= item.2;
 
-        Some(entity)
+            if is_throttled(&item.0, now) {
+                self.throttled.push(item);
+                continue;
+            }
+
+            return Some(item.0);
+        }
     }
 
     fn update_current(
@@ -261,7 +326,8 @@ impl SchedClassRq for FairClassRq {
         match flags {
             UpdateFlags::Yield => true,
             UpdateFlags::Tick | UpdateFlags::Wait => {
-                let (vruntime, weight) = attr.fair.update_vruntime(rt.delta);
+                let weight = attr.fair_weight();
+                let vruntime = attr.fair.update_vruntime(rt.delta, weight);
                 self.min_vruntime = match self.entities.peek() {
                     Some(Reverse(leftmost)) => vruntime.min(leftmost.key()),
                     None => vruntime,
@@ -269,6 +335,7 @@ impl SchedClassRq for FairClassRq {
 
                 rt.period_delta > self.time_slice(weight)
                     || vruntime > self.min_vruntime + self.vtime_slice()
+                    || attr.group().is_throttled(clocks_to_ns(rt.start))
             }
         }
     }
@@ -0,0 +1,198 @@
+// SPDX-License-Identifier: MPL-2.0
+
+use alloc::sync::Arc;
+use core::{
+    sync::atomic::{AtomicU32, AtomicU64, Ordering::Relaxed},
+    time::Duration,
+};
+
+use ostd::sync::SpinLock;
+use spin::Once;
+
+/// A group of threads that share CPU bandwidth.
+///
+/// This implements the CPU controller of cgroups. The groups form a hierarchy, and a thread in a
+/// group is also accounted to all the ancestors of the group.
+///
+/// # Weights
+///
+/// The weight of a fair thread is scaled by the weights of its group and the ancestors, where
+/// [`SchedGroup::DEFAULT_WEIGHT`] does not change the weight of the thread.
+///
+/// # Bandwidth limits
+///
+/// A group can run for at most `quota` in every `period`. Once a group has used up its quota, the
+/// fair threads in the group and its descendants are throttled until the next period starts.
+//
+// TODO: Schedule the groups hierarchically, so that the weights are only compared among the
+// sibling groups, like Linux's group scheduling.
+#[derive(Debug)]
+pub struct SchedGroup {
+    parent: Option<Arc<SchedGroup>>,
+    weight: AtomicU32,
+    bandwidth: SpinLock<Bandwidth>,
+    /// The total CPU time used by the threads in the group, measured in nanoseconds.
+    usage: AtomicU64,
+}
+
+#[derive(Debug)]
+struct Bandwidth {
+    quota: Option<u64>,
+    period: u64,
+    /// The start time of the current period, measured in nanoseconds.
+    period_start: u64,
+    /// The CPU time used in the current period, measured in nanoseconds.
+    runtime: u64,
+    is_throttled: bool,
+    nr_periods: u64,
+    nr_throttled: u64,
+}
+
+/// The statistics of the CPU usage of a [`SchedGroup`].
+#[derive(Debug, Clone, Copy)]
+pub struct SchedGroupStat {
+    pub usage: Duration,
+    pub nr_periods: u64,
+    pub nr_throttled: u64,
+}
+
+impl SchedGroup {
+    /// The weight of a new group.
+    pub const DEFAULT_WEIGHT: u32 = 100;
+    /// The minimum weight of a group.
+    pub const MIN_WEIGHT: u32 = 1;
+    /// The maximum weight of a group.
+    pub const MAX_WEIGHT: u32 = 10000;
+
+    /// The bandwidth period of a new group.
+    pub const DEFAULT_PERIOD: Duration = Duration::from_millis(100);
+
+    /// Returns the root group, which contains all the threads that are not in other groups.
+    pub fn root() -> &'static Arc<SchedGroup> {
+        static ROOT: Once<Arc<SchedGroup>> = Once::new();
+
+        ROOT.call_once(|| Arc::new(Self::new(None)))
+    }
+
+    /// Creates a child group with the default weight and no bandwidth limits.
+    pub fn new_child(self: &Arc<Self>) -> Arc<Self> {
+        Arc::new(Self::new(Some(self.clone())))
+    }
+
+    fn new(parent: Option<Arc<SchedGroup>>) -> Self {
+        Self {
+            parent,
+            weight: AtomicU32::new(Self::DEFAULT_WEIGHT),
+            bandwidth: SpinLock::new(Bandwidth {
+                quota: None,
+                period: Self::DEFAULT_PERIOD.as_nanos() as u64,
+                period_start: 0,
+                runtime: 0,
+                is_throttled: false,
+                nr_periods: 0,
+                nr_throttled: 0,
+            }),
+            usage: AtomicU64::new(0),
+        }
+    }
+
+    /// Returns the weight of the group.
+    pub fn weight(&self) -> u32 {
+        self.weight.load(Relaxed)
+    }
+
+    /// Sets the weight of the group.
+    ///
+    /// The weight should be in the range from [`Self::MIN_WEIGHT`] to [`Self::MAX_WEIGHT`].
+    pub fn set_weight(&self, weight: u32) {
+        debug_assert!((Self::MIN_WEIGHT..=Self::MAX_WEIGHT).contains(&weight));
+        self.weight.store(weight, Relaxed);
+    }
+
+    /// Returns the quota and the period of the group, where `None` means no limits.
+    pub fn max(&self) -> (Option<Duration>, Duration) {
+        let bandwidth = self.bandwidth.disable_irq().lock();
+        (
+            bandwidth.quota.map(Duration::from_nanos),
+            Duration::from_nanos(bandwidth.period),
+        )
+    }
+
+    /// Sets the quota and the period of the group, where `None` means no limits.
+    pub fn set_max(&self, quota: Option<Duration>, period: Duration) {
+        let mut bandwidth = self.bandwidth.disable_irq().lock();
+        bandwidth.quota = quota.map(|quota| quota.as_nanos() as u64);
+        bandwidth.period = period.as_nanos() as u64;
+        bandwidth.is_throttled = false;
+    }
+
+    /// Returns the statistics of the group.
+    pub fn stat(&self) -> SchedGroupStat {
+        let bandwidth = self.bandwidth.disable_irq().lock();
+        SchedGroupStat {
+            usage: Duration::from_nanos(self.usage.load(Relaxed)),
+            nr_periods: bandwidth.nr_periods,
+            nr_throttled: bandwidth.nr_throttled,
+        }
+    }
+
+    fn ancestors(&self) -> impl Iterator<Item = &SchedGroup> {
+        core::iter::successors(Some(self), |group| group.parent.as_deref())
+    }
+
+    /// Scales the weight of a fair thread in the group.
+    pub(super) fn scale_weight(&self, weight: u64) -> u64 {
+        self.ancestors()
+            .fold(weight, |weight, group| {
+                weight.saturating_mul(group.weight().into()) / u64::from(Self::DEFAULT_WEIGHT)
+            })
+            .max(1)
+    }
+
+    /// Charges the CPU time used by a thread in the group.
+    pub(super) fn charge(&self, delta: u64, now: u64) {
+        for group in self.ancestors() {
+            group.usage.fetch_add(delta, Relaxed);
+
+            let mut bandwidth = group.bandwidth.lock();
+            if bandwidth.quota.is_some() {
+                bandwidth.renew(now);
+                bandwidth.runtime += delta;
+            }
+        }
+    }
+
+    /// Returns whether the fair threads in the group should be throttled.
+    pub(super) fn is_throttled(&self, now: u64) -> bool {
+        self.ancestors().any(|group| {
+            let mut bandwidth = group.bandwidth.lock();
+            let Some(quota) = bandwidth.quota else {
+                return false;
+            };
+            bandwidth.renew(now);
+
+            if bandwidth.runtime < quota {
+                return false;
+            }
+            if !bandwidth.is_throttled {
+                bandwidth.is_throttled = true;
+                bandwidth.nr_throttled += 1;
+            }
+            true
+        })
+    }
+}
+
+impl Bandwidth {
+    /// Starts a new period if the current one has ended.
+    fn renew(&mut self, now: u64) {
+        if now < self.period_start + self.period {
+            return;
+        }
+
+        self.period_start = now - (now - self.period_start) % self.period;
+        self.runtime = 0;
+        self.is_throttled = false;
+        self.nr_periods += 1;
+    }
+}
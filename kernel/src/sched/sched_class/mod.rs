@@ -1,12 +1,14 @@
 // SPDX-License-Identifier: MPL-2.0
 
-#![warn(unused)]
-
-use alloc::{boxed::Box, sync::Arc};
-use core::{fmt, sync::atomic::AtomicU64};
+use alloc::{boxed::Box, sync::Arc, vec::Vec};
+use core::{
+    cmp, fmt,
+    sync::atomic::{AtomicU64, Ordering::Relaxed},
+};
 
+use aster_trace::{events::SCHED_SWITCH, trace_event};
 use ostd::{
-    cpu::{all_cpus, AtomicCpuSet, CpuId, PinCurrentCpu},
+    cpu::{hotplug, local::PerCpu, AtomicCpuSet, CpuId, CpuSet, PinCurrentCpu},
     sync::SpinLock,
     task::{
         scheduler::{
@@ -18,6 +20,7 @@ use ostd::{
     trap::disable_local,
 };
 
+mod group;
 mod policy;
 mod time;
 
@@ -28,37 +31,55 @@ mod stop;
 
 use ostd::arch::read_tsc as sched_clock;
 
-pub use self::policy::*;
-use self::policy::{SchedPolicyKind, SchedPolicyState};
+pub use self::{group::SchedGroup, policy::*};
+use self::{
+    policy::{SchedPolicyKind, SchedPolicyState},
+    time::clocks_to_ns,
+};
 use super::{
     priority::{Nice, RangedU8},
-    stats::SchedulerStats,
+    stats::{set_stats_from_scheduler, SchedulerStats},
+};
+use crate::{
+    process::posix_thread::AsPosixThread,
+    thread::{AsThread, Thread, Tid},
 };
-use crate::thread::{AsThread, Thread};
 
 type SchedEntity = (Arc<Task>, Arc<Thread>);
 
-#[allow(unused)]
 pub fn init() {
-    inject_scheduler(Box::leak(Box::new(ClassScheduler::new())));
+    let scheduler = Box::leak(Box::new(ClassScheduler::new()));
+
+    // Inject the scheduler into the ostd for actual scheduling work.
+    inject_scheduler(scheduler);
+
+    // Set the scheduler into the system for statistics.
+    // We set this after injecting the scheduler into ostd,
+    // so that the loadavg statistics are updated after the scheduler is used.
+    set_stats_from_scheduler(scheduler);
 }
 
 /// Represents the middle layer between scheduling classes and generic scheduler
 /// traits. It consists of all the sets of run queues for CPU cores. Other global
 /// information may also be stored here.
 pub struct ClassScheduler {
-    rqs: Box<[SpinLock<PerCpuClassRqSet>]>,
+    rqs: PerCpu<SpinLock<PerCpuClassRqSet>>,
 }
 
 /// Represents the run queue for each CPU core. It stores a list of run queues for
 /// scheduling classes in its corresponding CPU core. The current task of this CPU
 /// core is also stored in this structure.
 struct PerCpuClassRqSet {
+    cpu: CpuId,
     stop: stop::StopClassRq,
     real_time: real_time::RealTimeClassRq,
     fair: fair::FairClassRq,
     idle: idle::IdleClassRq,
     current: Option<(SchedEntity, CurrentRuntime)>,
+    /// The thread that was just switched out and is no longer allowed to run on this CPU.
+    ///
+    /// It will be moved to another CPU after the lock of this run queue is released.
+    migrating: Option<SchedEntity>,
 }
 
 /// Stores the runtime information of the current task.
@@ -119,6 +140,7 @@ trait SchedClassRq: Send + fmt::Debug {
 #[derive(Debug)]
 pub struct SchedAttr {
     policy: SchedPolicyState,
+    group: SpinLock<Arc<SchedGroup>>,
 
     real_time: real_time::RealTimeAttr,
     fair: fair::FairAttr,
@@ -129,6 +151,7 @@ impl SchedAttr {
     pub fn new(policy: SchedPolicy) -> Self {
         Self {
             policy: SchedPolicyState::new(policy),
+            group: SpinLock::new(SchedGroup::root().clone()),
             real_time: {
                 let (prio, policy) = match policy {
                     SchedPolicy::RealTime { rt_prio, rt_policy } => (rt_prio.get(), rt_policy),
@@ -157,13 +180,64 @@ impl SchedAttr {
     /// Specifically for real-time policies, if the new policy doesn't
     /// specify a base slice factor for RR, the old one will be kept.
     pub fn set_policy(&self, policy: SchedPolicy) {
-        self.policy.set(policy, |policy| match policy {
+        self.policy
+            .set(policy, |policy| self.update_class_attrs(policy));
+    }
+
+    /// Returns the real-time priority that the thread is actually scheduled with.
+    ///
+    /// This takes the inherited priority into account. `None` is returned if the thread is not
+    /// scheduled as a real-time thread.
+    pub fn effective_rt_prio(&self) -> Option<RtPrio> {
+        match self.policy.effective() {
+            SchedPolicy::Stop => Some(RtPrio::new(RtPrio::MIN)),
+            SchedPolicy::RealTime { rt_prio, .. } => Some(rt_prio),
+            SchedPolicy::Fair(_) | SchedPolicy::Idle => None,
+        }
+    }
+
+    /// Sets the priority inherited from other threads for priority inheritance (PI).
+    ///
+    /// If the inherited priority is higher than the priority of the thread's own policy, the
+    /// thread will be scheduled as a FIFO real-time thread with the inherited priority. The
+    /// policy returned by [`Self::policy`] is not affected.
+    pub fn set_pi_boost(&self, pi_boost: Option<RtPrio>) {
+        self.policy
+            .set_pi_boost(pi_boost, |policy| self.update_class_attrs(policy));
+    }
+
+    /// Sets the priority inherited from the threads that wait for the PI mutexes in the kernel.
+    ///
+    /// This works like [`Self::set_pi_boost`], and the higher one of the two inherited priorities
+    /// takes effect.
+    pub fn set_kernel_pi_boost(&self, kernel_pi_boost: Option<RtPrio>) {
+        self.policy
+            .set_kernel_pi_boost(kernel_pi_boost, |policy| self.update_class_attrs(policy));
+    }
+
+    /// Returns the group that the thread is accounted to.
+    pub fn group(&self) -> Arc<SchedGroup> {
+        self.group.disable_irq().lock().clone()
+    }
+
+    /// Moves the thread to another group.
+    pub fn set_group(&self, group: Arc<SchedGroup>) {
+        *self.group.disable_irq().lock() = group;
+    }
+
+    /// Returns the weight of the thread in the fair class, scaled by the weights of its group.
+    fn fair_weight(&self) -> u64 {
+        self.group().scale_weight(self.fair.weight())
+    }
+
+    fn update_class_attrs(&self, policy: SchedPolicy) {
+        match policy {
             SchedPolicy::RealTime { rt_prio, rt_policy } => {
                 self.real_time.update(rt_prio.get(), rt_policy);
             }
             SchedPolicy::Fair(nice) => self.fair.update(nice),
             _ => {}
-        });
+        }
     }
 }
 
@@ -172,7 +246,7 @@ impl Scheduler for ClassScheduler {
         let thread = task.as_thread()?.clone();
 
         let (still_in_rq, cpu) = {
-            let selected_cpu_id = self.select_cpu(thread.atomic_cpu_affinity());
+            let selected_cpu_id = self.select_cpu(&task, thread.atomic_cpu_affinity());
 
             if let Err(task_cpu_id) = task.cpu().set_if_is_none(selected_cpu_id) {
                 debug_assert!(flags != EnqueueFlags::Spawn);
@@ -182,26 +256,71 @@ impl Scheduler for ClassScheduler {
             }
         };
 
-        let mut rq = self.rqs[cpu.as_usize()].disable_irq().lock();
+        let mut rq = self.rqs.get_on_cpu(cpu).disable_irq().lock();
 
         // Note: call set_if_is_none again to prevent a race condition.
         if still_in_rq && task.cpu().set_if_is_none(cpu).is_err() {
             return None;
         }
 
-        rq.enqueue_entity((task, thread), Some(flags));
-        Some(cpu)
+        rq.enqueue_entity((task, thread.clone()), Some(flags));
+
+        rq.should_preempt(&thread).then_some(cpu)
     }
 
     fn local_mut_rq_with(&self, f: &mut dyn FnMut(&mut dyn LocalRunQueue)) {
         let guard = disable_local();
-        let mut lock = self.rqs[guard.current_cpu().as_usize()].lock();
-        f(&mut *lock)
+        let mut lock = self.rqs.get_with(&guard).lock();
+        f(&mut *lock);
+
+        let migrating = lock.migrating.take();
+        drop(lock);
+
+        if let Some((task, _)) = migrating {
+            self.migrate(task);
+        }
     }
 
     fn local_rq_with(&self, f: &mut dyn FnMut(&dyn LocalRunQueue)) {
         let guard = disable_local();
-        f(&*self.rqs[guard.current_cpu().as_usize()].lock())
+        f(&*self.rqs.get_with(&guard).lock())
+    }
+
+    fn inheritable_priority(&self, task: &Task) -> Option<u8> {
+        let rt_prio = task.as_thread()?.sched_attr().effective_rt_prio()?;
+        Some(rt_prio.get())
+    }
+
+    fn set_inherited_priority(&self, task: &Task, priority: Option<u8>) {
+        let Some(thread) = task.as_thread() else {
+            return;
+        };
+        thread
+            .sched_attr()
+            .set_kernel_pi_boost(priority.map(RtPrio::new));
+    }
+
+    fn offline_cpu(&self, cpu: CpuId) {
+        let migrating = {
+            let mut rq = self.rqs.get_on_cpu(cpu).disable_irq().lock();
+            let mut staying = Vec::new();
+            let mut migrating = Vec::new();
+            while let Some(entity) = rq.pick_next_entity() {
+                if may_stay_on(&entity.1, cpu) {
+                    staying.push(entity);
+                } else {
+                    migrating.push(entity);
+                }
+            }
+            for entity in staying {
+                rq.enqueue_entity(entity, None);
+            }
+            migrating
+        };
+
+        for (task, _) in migrating {
+            self.migrate(task);
+        }
     }
 }
 
@@ -214,26 +333,111 @@ impl ClassScheduler {
                 fair: fair::FairClassRq::new(cpu),
                 idle: idle::IdleClassRq::new(),
                 current: None,
+                migrating: None,
+                cpu,
             })
         };
         ClassScheduler {
-            rqs: all_cpus().map(class_rq).collect(),
+            rqs: PerCpu::new(class_rq),
         }
     }
 
-    // TODO: Implement a better algorithm and replace the current naive implementation.
-    fn select_cpu(&self, affinity: &AtomicCpuSet) -> CpuId {
+    /// Selects a CPU for the task to run on.
+    ///
+    /// If the task is still in a run queue, the CPU of that run queue is kept. Otherwise, the CPU
+    /// with the least load among the online CPUs in the affinity is selected, where the current
+    /// CPU is preferred if there is a tie. If no CPUs in the affinity are online, the affinity is
+    /// ignored.
+    //
+    // TODO: Migrate the tasks that are already in the run queues for load balancing. This is
+    // not safe until the task switching is guarded. See
+    // <https://github.com/asterinas/asterinas/issues/1471> for details.
+    fn select_cpu(&self, task: &Task, affinity: &AtomicCpuSet) -> CpuId {
+        if let Some(cpu_id) = task.cpu().get() {
+            return cpu_id;
+        }
+
         let guard = disable_local();
-        let affinity = affinity.load();
+        let affinity = online_cpus_in(&affinity.load());
         let cur = guard.current_cpu();
-        if affinity.contains(cur) {
-            cur
+
+        let load_of = |cpu: CpuId| self.rqs.get_on_cpu(cpu).lock().load();
+
+        let mut selected = cur;
+        let mut minimum_load = if affinity.contains(cur) {
+            load_of(cur)
         } else {
-            affinity.iter().next().expect("empty affinity")
+            u64::MAX
+        };
+        for candidate in affinity.iter() {
+            if candidate == cur {
+                continue;
+            }
+            let load = load_of(candidate);
+            if load < minimum_load {
+                selected = candidate;
+                minimum_load = load;
+            }
         }
+
+        debug_assert!(affinity.contains(selected), "empty affinity");
+        selected
+    }
+
+    /// Moves a runnable task that is not in any run queue to a CPU in its affinity.
+    //
+    // FIXME: The task may still be running on the current CPU until the context switch
+    // completes, but it can be picked by the target CPU before that. This is the same soundness
+    // hole as waking up a task that is being parked. See
+    // <https://github.com/asterinas/asterinas/issues/1471> for details.
+    fn migrate(&self, task: Arc<Task>) {
+        task.cpu().set_to_none();
+
+        // If the task is woken up concurrently, the waker will find that the task is no longer
+        // in any run queue and enqueue it. Otherwise, we enqueue it here.
+        //
+        // TODO: Notify the target CPU to preempt its current task if necessary.
+        let _ = self.enqueue(task, EnqueueFlags::Wake);
+    }
+}
+
+/// Returns the online CPUs in the affinity, or all the online CPUs if there are none.
+fn online_cpus_in(affinity: &CpuSet) -> CpuSet {
+    let online_cpus = hotplug::online_cpus();
+    let mut cpus = affinity.clone();
+    for cpu in affinity.iter() {
+        if !online_cpus.contains(cpu) {
+            cpus.remove(cpu);
+        }
+    }
+
+    if cpus.is_empty() {
+        online_cpus
+    } else {
+        cpus
     }
 }
 
+/// Returns whether the thread may stay on the CPU.
+///
+/// A thread must leave a CPU that is not in its affinity. It must also leave an offline CPU,
+/// unless it is pinned to the CPU, e.g., the idle thread of the CPU.
+fn may_stay_on(thread: &Thread, cpu: CpuId) -> bool {
+    let affinity = thread.atomic_cpu_affinity();
+    if !affinity.contains(cpu, Relaxed) {
+        return false;
+    }
+
+    hotplug::is_online(cpu) || affinity.load().count() == 1
+}
+
+/// Returns the TID of the thread, or zero if it is a kernel thread.
+fn tid_of(thread: &Thread) -> Tid {
+    thread
+        .as_posix_thread()
+        .map_or(0, |posix_thread| posix_thread.tid())
+}
+
 impl PerCpuClassRqSet {
     fn pick_next_entity(&mut self) -> Option<SchedEntity> {
         (self.stop.pick_next())
@@ -255,6 +459,43 @@ impl PerCpuClassRqSet {
         }
     }
 
+    /// Returns whether a newly enqueued thread should preempt the current thread.
+    fn should_preempt(&self, thread: &Thread) -> bool {
+        let Some(((_, cur), _)) = &self.current else {
+            return true;
+        };
+
+        let (new_attr, cur_attr) = (thread.sched_attr(), cur.sched_attr());
+        match new_attr.policy_kind().cmp(&cur_attr.policy_kind()) {
+            cmp::Ordering::Less => true,
+            cmp::Ordering::Greater => false,
+            cmp::Ordering::Equal => match new_attr.policy_kind() {
+                SchedPolicyKind::RealTime => real_time::should_preempt(new_attr, cur_attr),
+                SchedPolicyKind::Fair => self.fair.should_preempt(new_attr, cur_attr),
+                SchedPolicyKind::Stop | SchedPolicyKind::Idle => false,
+            },
+        }
+    }
+
+    /// Returns the load of the CPU, which is used to balance the threads among the CPUs.
+    ///
+    /// The load is the sum of the weights of the runnable threads, where a real-time thread is
+    /// considered as heavy as a fair thread with the highest priority.
+    fn load(&mut self) -> u64 {
+        let current_load = match &self.current {
+            Some(((_, cur), _)) => match cur.sched_attr().policy_kind() {
+                SchedPolicyKind::Stop | SchedPolicyKind::RealTime => fair::MAX_WEIGHT,
+                SchedPolicyKind::Fair => cur.sched_attr().fair_weight(),
+                SchedPolicyKind::Idle => 0,
+            },
+            None => 0,
+        };
+
+        current_load
+            + (self.stop.len() + self.real_time.len()) as u64 * fair::MAX_WEIGHT
+            + self.fair.total_weight()
+    }
+
     fn nr_queued_and_running(&mut self) -> (u32, u32) {
         let queued = self.stop.len() + self.real_time.len() + self.fair.len() + self.idle.len();
         let running = usize::from(self.current.is_some());
@@ -270,11 +511,18 @@ impl LocalRunQueue for PerCpuClassRqSet {
     fn pick_next_current(&mut self) -> Option<&Arc<Task>> {
         self.pick_next_entity().and_then(|next| {
             let next_ptr = Arc::as_ptr(&next.0);
+            let next_tid = tid_of(&next.1);
             if let Some((old, _)) = self.current.replace((next, CurrentRuntime::new())) {
                 if Arc::as_ptr(&old.0) == next_ptr {
                     return None;
                 }
-                self.enqueue_entity(old, None);
+                trace_event!(SCHED_SWITCH, tid_of(&old.1), next_tid);
+                if may_stay_on(&old.1, self.cpu) {
+                    self.enqueue_entity(old, None);
+                } else {
+                    debug_assert!(self.migrating.is_none());
+                    self.migrating = Some(old);
+                }
             }
             self.current.as_ref().map(|((task, _), _)| task)
         })
@@ -285,6 +533,14 @@ impl LocalRunQueue for PerCpuClassRqSet {
             rt.update();
             let attr = &cur.sched_attr();
 
+            let now = clocks_to_ns(rt.start);
+            if attr.policy_kind() != SchedPolicyKind::Idle {
+                attr.group().charge(clocks_to_ns(rt.delta), now);
+            }
+            if flags == UpdateFlags::Tick {
+                self.fair.unthrottle(now);
+            }
+
             let (current_expired, lookahead) = match attr.policy_kind() {
                 SchedPolicyKind::Stop => (self.stop.update_current(rt, attr, flags), 0),
                 SchedPolicyKind::RealTime => (self.real_time.update_current(rt, attr, flags), 1),
@@ -292,7 +548,10 @@ impl LocalRunQueue for PerCpuClassRqSet {
                 SchedPolicyKind::Idle => (self.idle.update_current(rt, attr, flags), 3),
             };
 
+            // The current thread must leave this CPU if it is no longer in the affinity or the
+            // CPU is going offline.
             current_expired
+                || !may_stay_on(cur, self.cpu)
                 || (lookahead >= 1 && !self.stop.is_empty())
                 || (lookahead >= 2 && !self.real_time.is_empty())
                 || (lookahead >= 3 && !self.fair.is_empty())
@@ -311,7 +570,7 @@ impl LocalRunQueue for PerCpuClassRqSet {
 
 impl SchedulerStats for ClassScheduler {
     fn nr_queued_and_running(&self) -> (u32, u32) {
-        self.rqs.iter().fold((0, 0), |(queued, running), rq| {
+        self.rqs.iter().fold((0, 0), |(queued, running), (_, rq)| {
             let (q, r) = rq.lock().nr_queued_and_running();
             (queued + q, running + r)
         })
@@ -6,7 +6,7 @@ use atomic_integer_wrapper::define_atomic_version_of_integer_like_type;
 use int_to_c_enum::TryFromInt;
 use ostd::sync::SpinLock;
 
-pub use super::real_time::RealTimePolicy;
+pub use super::real_time::{RealTimePolicy, RtPrio};
 use crate::sched::priority::{Nice, Priority, RangedU8};
 
 /// The User-chosen scheduling policy.
@@ -16,13 +16,19 @@ use crate::sched::priority::{Nice, Priority, RangedU8};
 pub enum SchedPolicy {
     Stop,
     RealTime {
-        rt_prio: super::real_time::RtPrio,
+        rt_prio: RtPrio,
         rt_policy: RealTimePolicy,
     },
     Fair(Nice),
     Idle,
 }
 
+impl Default for SchedPolicy {
+    fn default() -> Self {
+        Self::Fair(Nice::default())
+    }
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, TryFromInt)]
 #[repr(u8)]
 pub(super) enum SchedPolicyKind {
@@ -71,14 +77,53 @@ impl From<SchedPolicyKind> for u8 {
 #[derive(Debug)]
 pub(super) struct SchedPolicyState {
     kind: AtomicSchedPolicyKind,
-    policy: SpinLock<SchedPolicy>,
+    policy: SpinLock<BoostedPolicy>,
+}
+
+/// The user-chosen scheduling policy and the priorities inherited from other threads.
+#[derive(Debug, Clone, Copy)]
+struct BoostedPolicy {
+    policy: SchedPolicy,
+    /// The priority inherited through PI futexes.
+    pi_boost: Option<RtPrio>,
+    /// The priority inherited through the PI mutexes in the kernel.
+    kernel_pi_boost: Option<RtPrio>,
+}
+
+impl BoostedPolicy {
+    /// Returns the policy that the thread is actually scheduled with.
+    ///
+    /// A thread with an inherited priority is scheduled as a FIFO thread with that priority, unless
+    /// its own policy is more urgent.
+    fn effective(&self) -> SchedPolicy {
+        let Some(pi_boost) = self.pi_boost.into_iter().chain(self.kernel_pi_boost).min() else {
+            return self.policy;
+        };
+
+        match self.policy {
+            SchedPolicy::Stop => SchedPolicy::Stop,
+            SchedPolicy::RealTime { rt_prio, .. } if rt_prio <= pi_boost => self.policy,
+            SchedPolicy::RealTime { rt_policy, .. } => SchedPolicy::RealTime {
+                rt_prio: pi_boost,
+                rt_policy,
+            },
+            SchedPolicy::Fair(_) | SchedPolicy::Idle => SchedPolicy::RealTime {
+                rt_prio: pi_boost,
+                rt_policy: RealTimePolicy::Fifo,
+            },
+        }
+    }
 }
 
 impl SchedPolicyState {
     pub fn new(policy: SchedPolicy) -> Self {
         Self {
             kind: AtomicSchedPolicyKind::new(policy.kind()),
-            policy: SpinLock::new(policy),
+            policy: SpinLock::new(BoostedPolicy {
+                policy,
+                pi_boost: None,
+                kernel_pi_boost: None,
+            }),
         }
     }
 
@@ -87,7 +132,11 @@ impl SchedPolicyState {
     }
 
     pub fn get(&self) -> SchedPolicy {
-        *self.policy.disable_irq().lock()
+        self.policy.disable_irq().lock().policy
+    }
+
+    pub fn effective(&self) -> SchedPolicy {
+        self.policy.disable_irq().lock().effective()
     }
 
     pub fn set(&self, mut policy: SchedPolicy, update: impl FnOnce(SchedPolicy)) {
@@ -106,13 +155,42 @@ impl SchedPolicyState {
                 rt_policy: RealTimePolicy::RoundRobin { base_slice_factor },
                 ..
             },
-        ) = (*this, &mut policy)
+        ) = (this.policy, &mut policy)
         {
             *base_slice_factor = slot.or(*base_slice_factor);
         }
 
-        update(policy);
-        self.kind.store(policy.kind(), Relaxed);
-        *this = policy;
+        let new = BoostedPolicy { policy, ..*this };
+        Self::apply(&self.kind, new, update);
+        *this = new;
+    }
+
+    pub fn set_pi_boost(&self, pi_boost: Option<RtPrio>, update: impl FnOnce(SchedPolicy)) {
+        let mut this = self.policy.disable_irq().lock();
+
+        let new = BoostedPolicy { pi_boost, ..*this };
+        Self::apply(&self.kind, new, update);
+        *this = new;
+    }
+
+    pub fn set_kernel_pi_boost(
+        &self,
+        kernel_pi_boost: Option<RtPrio>,
+        update: impl FnOnce(SchedPolicy),
+    ) {
+        let mut this = self.policy.disable_irq().lock();
+
+        let new = BoostedPolicy {
+            kernel_pi_boost,
+            ..*this
+        };
+        Self::apply(&self.kind, new, update);
+        *this = new;
+    }
+
+    fn apply(kind: &AtomicSchedPolicyKind, new: BoostedPolicy, update: impl FnOnce(SchedPolicy)) {
+        let effective = new.effective();
+        update(effective);
+        kind.store(effective.kind(), Relaxed);
     }
 }
@@ -5,11 +5,15 @@ use core::{
     array,
     num::NonZero,
     sync::atomic::{AtomicU8, Ordering::*},
+    time::Duration,
 };
 
 use bitvec::{bitarr, BitArr};
 
-use super::{time::base_slice_clocks, *};
+use super::{
+    time::{base_slice_clocks, BASE_SLICE_NS},
+    *,
+};
 
 pub type RtPrio = RangedU8<1, 99>;
 
@@ -30,13 +34,19 @@ impl Default for RealTimePolicy {
 }
 
 impl RealTimePolicy {
+    /// Returns the time slice of an RR thread, or zero for a FIFO thread.
+    pub fn time_slice(self) -> Duration {
+        Duration::from_nanos(BASE_SLICE_NS * self.base_slice_factor())
+    }
+
     fn to_time_slice(self) -> u64 {
+        base_slice_clocks() * self.base_slice_factor()
+    }
+
+    fn base_slice_factor(self) -> u64 {
         match self {
-            RealTimePolicy::RoundRobin { base_slice_factor } => {
-                base_slice_clocks()
-                    * base_slice_factor
-                        .map_or(DEFAULT_BASE_SLICE_FACTOR, |factor| u64::from(factor.get()))
-            }
+            RealTimePolicy::RoundRobin { base_slice_factor } => base_slice_factor
+                .map_or(DEFAULT_BASE_SLICE_FACTOR, |factor| u64::from(factor.get())),
             RealTimePolicy::Fifo => 0,
         }
     }
@@ -49,10 +59,10 @@ impl RealTimePolicy {
 ///
 /// - If the time slice is not set, the thread is considered to be a FIFO
 ///   thread, and will be executed to its end if there no thread with a
-///   lower priority.
+///   higher priority.
 /// - If the time slice is set, the thread is considered to be an RR
 ///   (round-robin) thread, and will be executed for the time slice, and
-///   then it will be put back to the inactive array.
+///   then it will be put back to the tail of the queue of its priority.
 #[derive(Debug)]
 pub struct RealTimeAttr {
     prio: AtomicU8,
@@ -75,6 +85,14 @@ impl RealTimeAttr {
     }
 }
 
+/// Returns whether a newly enqueued thread should preempt the current thread.
+///
+/// Both threads are required to be in the REAL-TIME scheduling class. A lower value means a higher
+/// priority.
+pub(super) fn should_preempt(new_attr: &SchedAttr, cur_attr: &SchedAttr) -> bool {
+    new_attr.real_time.prio.load(Relaxed) < cur_attr.real_time.prio.load(Relaxed)
+}
+
 struct PrioArray {
     map: BitArr![for 100],
     queue: [VecDeque<Arc<Task>>; 100],
@@ -109,8 +127,7 @@ impl PrioArray {
     }
 
     fn pop(&mut self) -> Option<Arc<Task>> {
-        let mut iter = self.map.iter_ones();
-        let prio = iter.next()? as u8;
+        let prio = self.highest_prio()?;
 
         let queue = &mut self.queue[usize::from(prio)];
         let thread = queue.pop_front()?;
@@ -120,6 +137,11 @@ impl PrioArray {
         }
         Some(thread)
     }
+
+    /// Returns the highest priority (i.e., the lowest value) of the queued threads.
+    fn highest_prio(&self) -> Option<u8> {
+        self.map.first_one().map(|prio| prio as u8)
+    }
 }
 
 /// The per-cpu run queue for the REAL-TIME scheduling class.
@@ -130,15 +152,14 @@ impl PrioArray {
 /// It uses a bit array to track which priority levels have runnable
 /// threads, and a vector of queues to store the threads.
 ///
-/// Threads are popped & dequeued from the active array (`array[index]`), and
-/// are enqueued into the inactive array (`array[!index]`). When the active array
-/// is empty, the 2 arrays are swapped by `index`.
+/// The threads are dispatched strictly by their priorities, so a thread is
+/// never picked if there is a runnable thread with a higher priority. The
+/// threads with the same priority are picked in the order they are enqueued.
 #[derive(Debug)]
 pub(super) struct RealTimeClassRq {
     #[allow(unused)]
     cpu: CpuId,
-    index: bool,
-    array: [PrioArray; 2],
+    array: PrioArray,
     nr_running: usize,
 }
 
@@ -146,33 +167,20 @@ impl RealTimeClassRq {
     pub fn new(cpu: CpuId) -> RealTimeClassRq {
         RealTimeClassRq {
             cpu,
-            index: false,
-            array: array::from_fn(|_| PrioArray {
+            array: PrioArray {
                 map: bitarr![0; 100],
                 queue: array::from_fn(|_| VecDeque::new()),
-            }),
+            },
             nr_running: 0,
         }
     }
-
-    fn active_array(&mut self) -> &mut PrioArray {
-        &mut self.array[usize::from(self.index)]
-    }
-
-    fn inactive_array(&mut self) -> &mut PrioArray {
-        &mut self.array[usize::from(!self.index)]
-    }
-
-    fn swap_arrays(&mut self) {
-        self.index = !self.index;
-    }
 }
 
 impl SchedClassRq for RealTimeClassRq {
     fn enqueue(&mut self, entity: Arc<Task>, _: Option<EnqueueFlags>) {
         let sched_attr = entity.as_thread().unwrap().sched_attr();
         let prio = sched_attr.real_time.prio.load(Relaxed);
-        self.inactive_array().enqueue(entity, prio);
+        self.array.enqueue(entity, prio);
         self.nr_running += 1;
     }
 
@@ -185,16 +193,7 @@ impl SchedClassRq for RealTimeClassRq {
     }
 
     fn pick_next(&mut self) -> Option<Arc<Task>> {
-        if self.nr_running == 0 {
-            return None;
-        }
-
-        (self.active_array().pop())
-            .or_else(|| {
-                self.swap_arrays();
-                self.active_array().pop()
-            })
-            .inspect(|_| self.nr_running -= 1)
+        self.array.pop().inspect(|_| self.nr_running -= 1)
     }
 
     fn update_current(
@@ -206,11 +205,20 @@ impl SchedClassRq for RealTimeClassRq {
         let attr = &attr.real_time;
 
         match flags {
-            UpdateFlags::Tick | UpdateFlags::Wait => match attr.time_slice.load(Relaxed) {
-                0 => (self.inactive_array().map.iter_ones().next())
-                    .is_some_and(|prio| prio > usize::from(attr.prio.load(Relaxed))),
-                ts => ts <= rt.period_delta,
-            },
+            UpdateFlags::Tick | UpdateFlags::Wait => {
+                let Some(highest_prio) = self.array.highest_prio() else {
+                    return false;
+                };
+
+                let prio = attr.prio.load(Relaxed);
+                match attr.time_slice.load(Relaxed) {
+                    // A FIFO thread is only preempted by the threads with higher priorities.
+                    0 => highest_prio < prio,
+                    // An RR thread is also preempted by the threads with the same priority after
+                    // its time slice expires.
+                    ts => highest_prio < prio || (highest_prio == prio && ts <= rt.period_delta),
+                }
+            }
             UpdateFlags::Yield => true,
         }
     }
@@ -0,0 +1,619 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! The auditing subsystem, which records the system calls and the security events.
+//!
+//! The system calls are audited according to the rules, which are configured with `NETLINK_AUDIT`
+//! sockets (e.g., by `auditctl`). When auditing is enabled, each system call that matches a rule
+//! produces an `AUDIT_SYSCALL` record with the arguments, the exit value, and the credentials of
+//! the calling process. Some system calls produce auxiliary records with more details, e.g.,
+//! `AUDIT_EXECVE` for the arguments of `execve` and `AUDIT_PATH` for the names of the files. The
+//! records of a system call share the same timestamp and serial number, and the last record is
+//! always `AUDIT_EOE`.
+//!
+//! The records are delivered to the audit daemon (e.g., `auditd`), which registers itself with its
+//! `NETLINK_AUDIT` socket. If there is no audit daemon, the records are logged and kept in the
+//! backlog, which is delivered to the next audit daemon.
+//!
+//! Unlike Linux, only the exit filter list is supported, and there is no login UID or session ID,
+//! so `auid` and `ses` are always unset in the records.
+//!
+//! For more details, see <https://man7.org/linux/man-pages/man8/auditctl.8.html>.
+
+mod rule;
+
+use core::{
+    fmt::Write,
+    sync::atomic::{AtomicBool, AtomicU32, Ordering},
+    time::Duration,
+};
+
+use rule::SyscallInfo;
+pub use rule::{
+    AuditAction, AuditField, AuditRule, FieldType, Operator, MAX_FIELDS, SYSCALL_MASK_LEN,
+};
+
+use crate::{
+    prelude::*,
+    process::{posix_thread::AsPosixThread, Pid},
+    syscall::SyscallReturn,
+    time::clocks::RealTimeClock,
+};
+
+/// The types of the records that are generated by the kernel.
+#[repr(u16)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[allow(non_camel_case_types)]
+pub enum RecordType {
+    AUDIT_SYSCALL = 1300,
+    AUDIT_PATH = 1302,
+    AUDIT_CONFIG_CHANGE = 1305,
+    AUDIT_EXECVE = 1309,
+    AUDIT_EOE = 1320,
+}
+
+/// The failure mode that ignores the lost records.
+pub const AUDIT_FAIL_SILENT: u32 = 0;
+/// The failure mode that logs the lost records.
+pub const AUDIT_FAIL_PRINTK: u32 = 1;
+/// The failure mode that panics the kernel if a record is lost.
+pub const AUDIT_FAIL_PANIC: u32 = 2;
+
+/// The value of `enabled` that locks the configuration until the system reboots.
+pub const AUDIT_LOCKED: u32 = 2;
+
+/// The value of the login UID and the session ID that is unset.
+const UNSET_ID: u32 = u32::MAX;
+
+/// A record, which is a line of text with a type.
+#[derive(Debug, Clone)]
+pub struct AuditRecord {
+    type_: u16,
+    text: String,
+}
+
+impl AuditRecord {
+    fn new(type_: u16, stamp: &Stamp, body: &str) -> Self {
+        let text = format!(
+            "audit({}.{:03}:{}): {}",
+            stamp.time.as_secs(),
+            stamp.time.subsec_millis(),
+            stamp.serial,
+            body
+        );
+        Self { type_, text }
+    }
+
+    pub fn type_(&self) -> u16 {
+        self.type_
+    }
+
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+}
+
+/// The timestamp and the serial number that identify an event.
+#[derive(Debug, Clone, Copy)]
+struct Stamp {
+    time: Duration,
+    serial: u32,
+}
+
+impl Stamp {
+    fn new() -> Self {
+        static SERIAL: AtomicU32 = AtomicU32::new(0);
+
+        Self {
+            time: RealTimeClock::get().read_time(),
+            serial: SERIAL.fetch_add(1, Ordering::Relaxed).wrapping_add(1),
+        }
+    }
+}
+
+/// The audit daemon, to which the records are delivered.
+pub trait AuditDaemon: Send + Sync {
+    /// Returns the process ID of the daemon.
+    fn pid(&self) -> Pid;
+
+    /// Returns whether the daemon can still receive records.
+    fn is_alive(&self) -> bool;
+
+    /// Delivers a record to the daemon.
+    fn deliver(&self, record: &AuditRecord);
+}
+
+/// The status of the auditing subsystem (`struct audit_status`).
+#[derive(Debug, Clone, Copy)]
+pub struct AuditStatus {
+    pub enabled: u32,
+    pub failure: u32,
+    pub pid: Pid,
+    pub rate_limit: u32,
+    pub backlog_limit: u32,
+    pub lost: u32,
+    pub backlog: u32,
+}
+
+struct AuditState {
+    enabled: u32,
+    failure: u32,
+    daemon: Option<Box<dyn AuditDaemon>>,
+    /// The maximum number of records per second, or zero if there is no limit.
+    rate_limit: u32,
+    /// The second and the number of records that have been generated in the second.
+    rate_count: (u64, u32),
+    /// The maximum number of records in the backlog, or zero if there is no limit.
+    backlog_limit: u32,
+    backlog: VecDeque<AuditRecord>,
+    lost: u32,
+    /// The rules of the exit filter list, in the order that they are matched.
+    rules: Vec<AuditRule>,
+}
+
+/// Whether any system call may be audited, i.e., auditing is enabled and there are rules.
+///
+/// It is checked at the entry of every system call to avoid locking the state.
+static IS_ACTIVE: AtomicBool = AtomicBool::new(false);
+
+static STATE: Mutex<AuditState> = Mutex::new(AuditState {
+    enabled: 0,
+    failure: AUDIT_FAIL_PRINTK,
+    daemon: None,
+    rate_limit: 0,
+    rate_count: (0, 0),
+    backlog_limit: 64,
+    backlog: VecDeque::new(),
+    lost: 0,
+    rules: Vec::new(),
+});
+
+impl AuditState {
+    fn update_active(&self) {
+        IS_ACTIVE.store(
+            self.enabled != 0 && !self.rules.is_empty(),
+            Ordering::Relaxed,
+        );
+    }
+
+    fn check_unlocked(&self) -> Result<()> {
+        if self.enabled == AUDIT_LOCKED {
+            return_errno_with_message!(Errno::EPERM, "the audit configuration is locked");
+        }
+        Ok(())
+    }
+
+    /// Emits a record, which is delivered to the daemon or kept in the backlog.
+    fn emit(&mut self, record: AuditRecord) {
+        if self.rate_limit != 0 {
+            let now = RealTimeClock::get().read_time().as_secs();
+            if self.rate_count.0 != now {
+                self.rate_count = (now, 0);
+            }
+            if self.rate_count.1 >= self.rate_limit {
+                self.lose("rate limit exceeded");
+                return;
+            }
+            self.rate_count.1 += 1;
+        }
+
+        if let Some(daemon) = self.daemon.as_ref() {
+            if daemon.is_alive() {
+                daemon.deliver(&record);
+                return;
+            }
+            self.daemon = None;
+        }
+
+        if self.backlog_limit != 0 && self.backlog.len() >= self.backlog_limit as usize {
+            self.lose("backlog limit exceeded");
+            return;
+        }
+        info!("audit: type={} {}", record.type_, record.text);
+        self.backlog.push_back(record);
+    }
+
+    fn lose(&mut self, reason: &str) {
+        self.lost = self.lost.wrapping_add(1);
+        match self.failure {
+            AUDIT_FAIL_SILENT => (),
+            AUDIT_FAIL_PRINTK => warn!("audit: {}, {} records lost", reason, self.lost),
+            _ => panic!("audit: {}, {} records lost", reason, self.lost),
+        }
+    }
+
+    fn log_config_change(&mut self, body: &str) {
+        let record = AuditRecord::new(
+            RecordType::AUDIT_CONFIG_CHANGE as u16,
+            &Stamp::new(),
+            &format!("auid={} ses={} {} res=1", UNSET_ID, UNSET_ID, body),
+        );
+        self.emit(record);
+    }
+}
+
+/// Returns the status of the auditing subsystem.
+pub fn status() -> AuditStatus {
+    let state = STATE.lock();
+
+    AuditStatus {
+        enabled: state.enabled,
+        failure: state.failure,
+        pid: state
+            .daemon
+            .as_ref()
+            .filter(|daemon| daemon.is_alive())
+            .map_or(0, |daemon| daemon.pid()),
+        rate_limit: state.rate_limit,
+        backlog_limit: state.backlog_limit,
+        lost: state.lost,
+        backlog: state.backlog.len() as u32,
+    }
+}
+
+/// Enables (1) or disables (0) auditing, or locks (2) the configuration.
+///
+/// This method fails with `EPERM` if the configuration is locked.
+pub fn set_enabled(enabled: u32) -> Result<()> {
+    if enabled > AUDIT_LOCKED {
+        return_errno_with_message!(Errno::EINVAL, "the enabled state is invalid");
+    }
+
+    let mut state = STATE.lock();
+    state.check_unlocked()?;
+
+    let old = state.enabled;
+    state.enabled = enabled;
+    state.update_active();
+    state.log_config_change(&format!("audit_enabled={} old={}", enabled, old));
+
+    Ok(())
+}
+
+/// Sets the failure mode.
+///
+/// This method fails with `EPERM` if the configuration is locked.
+pub fn set_failure(failure: u32) -> Result<()> {
+    if failure > AUDIT_FAIL_PANIC {
+        return_errno_with_message!(Errno::EINVAL, "the failure mode is invalid");
+    }
+
+    let mut state = STATE.lock();
+    state.check_unlocked()?;
+
+    let old = state.failure;
+    state.failure = failure;
+    state.log_config_change(&format!("audit_failure={} old={}", failure, old));
+
+    Ok(())
+}
+
+/// Sets the maximum number of records per second, where zero means no limit.
+pub fn set_rate_limit(rate_limit: u32) -> Result<()> {
+    let mut state = STATE.lock();
+    state.check_unlocked()?;
+
+    state.rate_limit = rate_limit;
+    Ok(())
+}
+
+/// Sets the maximum number of records in the backlog, where zero means no limit.
+pub fn set_backlog_limit(backlog_limit: u32) -> Result<()> {
+    let mut state = STATE.lock();
+    state.check_unlocked()?;
+
+    state.backlog_limit = backlog_limit;
+    Ok(())
+}
+
+/// Resets the number of lost records, and returns the old number.
+pub fn reset_lost() -> u32 {
+    core::mem::take(&mut STATE.lock().lost)
+}
+
+/// Registers the audit daemon, to which the records are delivered.
+///
+/// The records in the backlog are delivered immediately. This method fails with `EEXIST` if
+/// there is another audit daemon that is alive.
+pub fn set_daemon(daemon: Box<dyn AuditDaemon>) -> Result<()> {
+    let mut state = STATE.lock();
+
+    if state
+        .daemon
+        .as_ref()
+        .is_some_and(|daemon| daemon.is_alive())
+    {
+        return_errno_with_message!(Errno::EEXIST, "another audit daemon is registered");
+    }
+
+    for record in state.backlog.drain(..) {
+        daemon.deliver(&record);
+    }
+    state.daemon = Some(daemon);
+
+    Ok(())
+}
+
+/// Unregisters the audit daemon of the process, if any.
+///
+/// This method fails with `EACCES` if the audit daemon that is alive belongs to another process.
+pub fn clear_daemon(pid: Pid) -> Result<()> {
+    let mut state = STATE.lock();
+
+    if state
+        .daemon
+        .as_ref()
+        .is_some_and(|daemon| daemon.is_alive() && daemon.pid() != pid)
+    {
+        return_errno_with_message!(Errno::EACCES, "only the audit daemon can unregister itself");
+    }
+
+    state.daemon = None;
+    Ok(())
+}
+
+/// Adds a rule to the end of the exit filter list.
+///
+/// This method fails with `EEXIST` if the same rule exists.
+pub fn add_rule(rule: AuditRule) -> Result<()> {
+    let mut state = STATE.lock();
+    state.check_unlocked()?;
+
+    if state.rules.contains(&rule) {
+        return_errno_with_message!(Errno::EEXIST, "the rule already exists");
+    }
+
+    let body = format!("op=add_rule key={} list=4", format_key(rule.key()));
+    state.rules.push(rule);
+    state.update_active();
+    state.log_config_change(&body);
+
+    Ok(())
+}
+
+/// Deletes a rule from the exit filter list.
+///
+/// This method fails with `ENOENT` if the rule does not exist.
+pub fn del_rule(rule: &AuditRule) -> Result<()> {
+    let mut state = STATE.lock();
+    state.check_unlocked()?;
+
+    let Some(index) = state.rules.iter().position(|existing| existing == rule) else {
+        return_errno_with_message!(Errno::ENOENT, "the rule does not exist");
+    };
+
+    state.rules.remove(index);
+    state.update_active();
+    state.log_config_change(&format!(
+        "op=remove_rule key={} list=4",
+        format_key(rule.key())
+    ));
+
+    Ok(())
+}
+
+/// Returns the rules of the exit filter list.
+pub fn rules() -> Vec<AuditRule> {
+    STATE.lock().rules.clone()
+}
+
+/// Logs a message from the current process (e.g., `AUDIT_USER`).
+///
+/// The message is ignored if auditing is disabled.
+pub fn log_user_message(type_: u16, message: &[u8]) {
+    let mut state = STATE.lock();
+    if state.enabled == 0 {
+        return;
+    }
+
+    let current = current_thread!();
+    let posix_thread = current.as_posix_thread().unwrap();
+    // Like Linux, the message is quoted as is, since it comes from a trusted program.
+    let body = format!(
+        "pid={} uid={} auid={} ses={} msg='{}'",
+        posix_thread.process().pid(),
+        u32::from(posix_thread.credentials().ruid()),
+        UNSET_ID,
+        UNSET_ID,
+        String::from_utf8_lossy(message),
+    );
+    state.emit(AuditRecord::new(type_, &Stamp::new(), &body));
+}
+
+/// The audit context of a system call, which collects the information for the records.
+pub struct AuditContext {
+    stamp: Stamp,
+    /// The audit architecture, which tells the ABI that the system call follows.
+    arch: u32,
+    syscall_number: u64,
+    args: [u64; 6],
+    /// The bodies of the auxiliary records.
+    aux_records: Vec<(RecordType, String)>,
+    num_paths: usize,
+}
+
+/// Starts auditing the system call of the current thread if it may match a rule.
+pub fn syscall_entry(ctx: &Context, arch: u32, syscall_number: u64, args: [u64; 6]) {
+    if !IS_ACTIVE.load(Ordering::Relaxed) {
+        return;
+    }
+    if !STATE
+        .lock()
+        .rules
+        .iter()
+        .any(|rule| rule.has_syscall(syscall_number))
+    {
+        return;
+    }
+
+    *ctx.thread_local.audit_context().borrow_mut() = Some(AuditContext {
+        stamp: Stamp::new(),
+        arch,
+        syscall_number,
+        args,
+        aux_records: Vec::new(),
+        num_paths: 0,
+    });
+}
+
+/// Finishes auditing the system call of the current thread, and emits the records if the system
+/// call matches a rule.
+pub fn syscall_exit(ctx: &Context, result: &Result<SyscallReturn>) {
+    let Some(audit_context) = ctx.thread_local.audit_context().borrow_mut().take() else {
+        return;
+    };
+
+    let exit = match result {
+        Ok(SyscallReturn::Return(value)) => *value,
+        Ok(SyscallReturn::NoReturn) => 0,
+        Err(err) => -(err.error() as isize),
+    };
+    let syscall = {
+        let credentials = ctx.posix_thread.credentials();
+        SyscallInfo {
+            arch: audit_context.arch,
+            number: audit_context.syscall_number,
+            args: audit_context.args,
+            exit,
+            pid: ctx.process.pid(),
+            ppid: ctx.process.parent().pid(),
+            uids: [
+                credentials.ruid().into(),
+                credentials.euid().into(),
+                credentials.suid().into(),
+                credentials.fsuid().into(),
+            ],
+            gids: [
+                credentials.rgid().into(),
+                credentials.egid().into(),
+                credentials.sgid().into(),
+                credentials.fsgid().into(),
+            ],
+        }
+    };
+
+    let mut state = STATE.lock();
+    if state.enabled == 0 {
+        return;
+    }
+    // Like Linux, the first matching rule decides whether the system call is audited.
+    let Some(rule) = state.rules.iter().find(|rule| rule.matches(&syscall)) else {
+        return;
+    };
+    if rule.action() == AuditAction::Never {
+        return;
+    }
+    let key = format_key(rule.key());
+
+    let stamp = &audit_context.stamp;
+    let body = format_syscall(&syscall, audit_context.num_paths, &key, ctx);
+    state.emit(AuditRecord::new(
+        RecordType::AUDIT_SYSCALL as u16,
+        stamp,
+        &body,
+    ));
+    for (type_, body) in audit_context.aux_records.iter() {
+        state.emit(AuditRecord::new(*type_ as u16, stamp, body));
+    }
+    state.emit(AuditRecord::new(RecordType::AUDIT_EOE as u16, stamp, ""));
+}
+
+/// Records the arguments of `execve` if the system call is audited.
+pub fn log_execve(argv: &[CString], ctx: &Context) {
+    let mut audit_context = ctx.thread_local.audit_context().borrow_mut();
+    let Some(audit_context) = audit_context.as_mut() else {
+        return;
+    };
+
+    let mut body = format!("argc={}", argv.len());
+    for (i, arg) in argv.iter().enumerate() {
+        let _ = write!(body, " a{}={}", i, format_str(arg.as_bytes()));
+    }
+    audit_context
+        .aux_records
+        .push((RecordType::AUDIT_EXECVE, body));
+}
+
+/// Records the name of a file that is looked up by the system call if the system call is audited.
+pub fn log_path(name: &str, ctx: &Context) {
+    let mut audit_context = ctx.thread_local.audit_context().borrow_mut();
+    let Some(audit_context) = audit_context.as_mut() else {
+        return;
+    };
+
+    let body = format!(
+        "item={} name={}",
+        audit_context.num_paths,
+        format_str(name.as_bytes())
+    );
+    audit_context
+        .aux_records
+        .push((RecordType::AUDIT_PATH, body));
+    audit_context.num_paths += 1;
+}
+
+fn format_syscall(syscall: &SyscallInfo, num_paths: usize, key: &str, ctx: &Context) -> String {
+    let comm = {
+        let thread_name = ctx.posix_thread.thread_name().lock();
+        thread_name
+            .as_ref()
+            .and_then(|name| name.name().ok().flatten())
+            .map(|name| name.to_bytes().to_vec())
+            .unwrap_or_default()
+    };
+
+    format!(
+        "arch={:x} syscall={} success={} exit={} a0={:x} a1={:x} a2={:x} a3={:x} items={} \
+         ppid={} pid={} auid={} uid={} gid={} euid={} suid={} fsuid={} egid={} sgid={} fsgid={} \
+         ses={} comm={} exe={} key={}",
+        syscall.arch,
+        syscall.number,
+        if syscall.is_success() { "yes" } else { "no" },
+        syscall.exit,
+        syscall.args[0],
+        syscall.args[1],
+        syscall.args[2],
+        syscall.args[3],
+        num_paths,
+        syscall.ppid,
+        syscall.pid,
+        UNSET_ID,
+        syscall.uids[0],
+        syscall.gids[0],
+        syscall.uids[1],
+        syscall.uids[2],
+        syscall.uids[3],
+        syscall.gids[1],
+        syscall.gids[2],
+        syscall.gids[3],
+        UNSET_ID,
+        format_str(&comm),
+        format_str(ctx.process.executable_path().as_bytes()),
+        key,
+    )
+}
+
+fn format_key(key: Option<&str>) -> String {
+    match key {
+        Some(key) => format_str(key.as_bytes()),
+        None => String::from("(null)"),
+    }
+}
+
+/// Formats an untrusted string in a record.
+///
+/// Like Linux, the string is quoted if it only consists of printable characters other than the
+/// double quote, or it is encoded in hexadecimal otherwise, so that it cannot forge other fields.
+fn format_str(bytes: &[u8]) -> String {
+    let is_printable = bytes
+        .iter()
+        .all(|byte| (0x21..=0x7e).contains(byte) && *byte != b'"');
+    if is_printable {
+        return format!("\"{}\"", core::str::from_utf8(bytes).unwrap());
+    }
+
+    let mut hex = String::with_capacity(bytes.len() * 2);
+    for byte in bytes {
+        let _ = write!(hex, "{:02X}", byte);
+    }
+    hex
+}
@@ -0,0 +1,229 @@
+// SPDX-License-Identifier: MPL-2.0
+
+use crate::prelude::*;
+
+/// The number of 32-bit words in the system call mask of a rule (`AUDIT_BITMASK_SIZE`).
+pub const SYSCALL_MASK_LEN: usize = 64;
+
+/// The maximum number of fields in a rule (`AUDIT_MAX_FIELDS`).
+pub const MAX_FIELDS: usize = 64;
+
+/// The maximum length of the key of a rule (`AUDIT_MAX_KEY_LEN`).
+pub const MAX_KEY_LEN: usize = 256;
+
+/// The action of a rule (`AUDIT_NEVER` and `AUDIT_ALWAYS`).
+#[repr(u32)]
+#[derive(Debug, Clone, Copy, TryFromInt, PartialEq, Eq)]
+pub enum AuditAction {
+    /// The system call is not audited.
+    Never = 0,
+    /// The system call is audited.
+    Always = 2,
+}
+
+/// The types of the fields of a rule.
+///
+/// The key of a rule (`AUDIT_FILTERKEY`) is not a field here, see [`AuditRule::key`].
+///
+/// See <https://elixir.bootlin.com/linux/v6.0.9/source/include/uapi/linux/audit.h>.
+#[repr(u32)]
+#[derive(Debug, Clone, Copy, TryFromInt, PartialEq, Eq)]
+#[allow(non_camel_case_types)]
+pub enum FieldType {
+    AUDIT_PID = 0,
+    AUDIT_UID = 1,
+    AUDIT_EUID = 2,
+    AUDIT_SUID = 3,
+    AUDIT_FSUID = 4,
+    AUDIT_GID = 5,
+    AUDIT_EGID = 6,
+    AUDIT_SGID = 7,
+    AUDIT_FSGID = 8,
+    AUDIT_ARCH = 11,
+    AUDIT_PPID = 18,
+    AUDIT_EXIT = 103,
+    AUDIT_SUCCESS = 104,
+    AUDIT_ARG0 = 200,
+    AUDIT_ARG1 = 201,
+    AUDIT_ARG2 = 202,
+    AUDIT_ARG3 = 203,
+}
+
+/// The comparison operators of the fields of a rule.
+#[repr(u32)]
+#[derive(Debug, Clone, Copy, TryFromInt, PartialEq, Eq)]
+#[allow(non_camel_case_types)]
+pub enum Operator {
+    AUDIT_BIT_MASK = 0x0800_0000,
+    AUDIT_LESS_THAN = 0x1000_0000,
+    AUDIT_GREATER_THAN = 0x2000_0000,
+    AUDIT_NOT_EQUAL = 0x3000_0000,
+    AUDIT_EQUAL = 0x4000_0000,
+    AUDIT_BIT_TEST = 0x4800_0000,
+    AUDIT_LESS_THAN_OR_EQUAL = 0x5000_0000,
+    AUDIT_GREATER_THAN_OR_EQUAL = 0x6000_0000,
+}
+
+impl Operator {
+    fn compare(self, left: i64, right: i64) -> bool {
+        match self {
+            Self::AUDIT_BIT_MASK => left & right != 0,
+            Self::AUDIT_LESS_THAN => left < right,
+            Self::AUDIT_GREATER_THAN => left > right,
+            Self::AUDIT_NOT_EQUAL => left != right,
+            Self::AUDIT_EQUAL => left == right,
+            Self::AUDIT_BIT_TEST => left & right == right,
+            Self::AUDIT_LESS_THAN_OR_EQUAL => left <= right,
+            Self::AUDIT_GREATER_THAN_OR_EQUAL => left >= right,
+        }
+    }
+}
+
+/// A field of a rule, which compares a property of the system call with a value.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AuditField {
+    pub type_: FieldType,
+    pub op: Operator,
+    pub value: u32,
+}
+
+impl AuditField {
+    /// Creates a field.
+    ///
+    /// This method fails with `EINVAL` if the operator cannot be used with the field type.
+    pub fn new(type_: FieldType, op: Operator, value: u32) -> Result<Self> {
+        let is_equality = matches!(op, Operator::AUDIT_EQUAL | Operator::AUDIT_NOT_EQUAL);
+        if matches!(type_, FieldType::AUDIT_ARCH | FieldType::AUDIT_SUCCESS) && !is_equality {
+            return_errno_with_message!(
+                Errno::EINVAL,
+                "the field can only be compared for equality"
+            );
+        }
+
+        Ok(Self { type_, op, value })
+    }
+
+    fn matches(&self, syscall: &SyscallInfo) -> bool {
+        let value = match self.type_ {
+            // The exit value is signed, so that it can be compared with the error codes.
+            FieldType::AUDIT_EXIT => self.value as i32 as i64,
+            // Any nonzero value means success.
+            FieldType::AUDIT_SUCCESS => (self.value != 0) as i64,
+            _ => self.value as i64,
+        };
+
+        self.op.compare(syscall.field(self.type_), value)
+    }
+}
+
+/// A rule that decides whether the system calls are audited.
+///
+/// A rule matches a system call if the system call is in the mask of the rule and all the fields
+/// of the rule match the system call.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AuditRule {
+    action: AuditAction,
+    syscall_mask: [u32; SYSCALL_MASK_LEN],
+    fields: Vec<AuditField>,
+    key: Option<String>,
+}
+
+impl AuditRule {
+    /// Creates a rule.
+    ///
+    /// This method fails with `EINVAL` if there are too many fields or the key is too long.
+    pub fn new(
+        action: AuditAction,
+        syscall_mask: [u32; SYSCALL_MASK_LEN],
+        fields: Vec<AuditField>,
+        key: Option<String>,
+    ) -> Result<Self> {
+        if fields.len() + key.is_some() as usize > MAX_FIELDS {
+            return_errno_with_message!(Errno::EINVAL, "the rule has too many fields");
+        }
+        if key.as_ref().is_some_and(|key| key.len() > MAX_KEY_LEN) {
+            return_errno_with_message!(Errno::EINVAL, "the key of the rule is too long");
+        }
+
+        Ok(Self {
+            action,
+            syscall_mask,
+            fields,
+            key,
+        })
+    }
+
+    pub fn action(&self) -> AuditAction {
+        self.action
+    }
+
+    pub fn syscall_mask(&self) -> &[u32; SYSCALL_MASK_LEN] {
+        &self.syscall_mask
+    }
+
+    pub fn fields(&self) -> &[AuditField] {
+        &self.fields
+    }
+
+    /// Returns the key of the rule, which is included in the records to identify the rule.
+    pub fn key(&self) -> Option<&str> {
+        self.key.as_deref()
+    }
+
+    /// Returns whether the system call is in the mask of the rule.
+    pub(super) fn has_syscall(&self, syscall_number: u64) -> bool {
+        let (word, bit) = (syscall_number as usize / 32, syscall_number % 32);
+        self.syscall_mask
+            .get(word)
+            .is_some_and(|mask| mask & (1 << bit) != 0)
+    }
+
+    /// Returns whether the rule matches the system call.
+    pub(super) fn matches(&self, syscall: &SyscallInfo) -> bool {
+        self.has_syscall(syscall.number) && self.fields.iter().all(|field| field.matches(syscall))
+    }
+}
+
+/// The information of a system call that is compared with the fields of the rules.
+#[derive(Debug)]
+pub(super) struct SyscallInfo {
+    pub(super) arch: u32,
+    pub(super) number: u64,
+    pub(super) args: [u64; 6],
+    pub(super) exit: isize,
+    pub(super) pid: u32,
+    pub(super) ppid: u32,
+    pub(super) uids: [u32; 4],
+    pub(super) gids: [u32; 4],
+}
+
+impl SyscallInfo {
+    fn field(&self, type_: FieldType) -> i64 {
+        let value = match type_ {
+            FieldType::AUDIT_PID => self.pid,
+            FieldType::AUDIT_UID => self.uids[0],
+            FieldType::AUDIT_EUID => self.uids[1],
+            FieldType::AUDIT_SUID => self.uids[2],
+            FieldType::AUDIT_FSUID => self.uids[3],
+            FieldType::AUDIT_GID => self.gids[0],
+            FieldType::AUDIT_EGID => self.gids[1],
+            FieldType::AUDIT_SGID => self.gids[2],
+            FieldType::AUDIT_FSGID => self.gids[3],
+            FieldType::AUDIT_ARCH => self.arch,
+            FieldType::AUDIT_PPID => self.ppid,
+            FieldType::AUDIT_EXIT => return self.exit as i64,
+            FieldType::AUDIT_SUCCESS => return self.is_success() as i64,
+            // Like Linux, only the lower 32 bits of the arguments are compared.
+            FieldType::AUDIT_ARG0 => self.args[0] as u32,
+            FieldType::AUDIT_ARG1 => self.args[1] as u32,
+            FieldType::AUDIT_ARG2 => self.args[2] as u32,
+            FieldType::AUDIT_ARG3 => self.args[3] as u32,
+        };
+
+        value as i64
+    }
+
+    pub(super) fn is_success(&self) -> bool {
+        self.exit >= 0
+    }
+}
@@ -0,0 +1,765 @@
+// SPDX-License-Identifier: MPL-2.0
+
+use core::time::Duration;
+
+use aster_rights::ReadOp;
+
+use crate::{
+    prelude::*,
+    process::{credentials::capabilities::CapSet, Credentials, Gid, Uid},
+    time::clocks::MonotonicClock,
+    util::random::getrandom,
+};
+
+/// The serial number of a key, which identifies the key in the user space.
+pub type KeySerial = i32;
+
+/// The maximum length of the description of a key.
+pub const MAX_DESC_LEN: usize = 4095;
+
+/// The maximum size of the payload of a user or logon key.
+const MAX_USER_PAYLOAD_LEN: usize = 32767;
+
+/// The maximum depth of the nested keyrings that are searched.
+const MAX_SEARCH_DEPTH: usize = 6;
+
+/// The GID of the keys without a group (e.g., the user keyrings).
+pub(super) const INVALID_GID: Gid = Gid::new(u32::MAX);
+
+/// All the keys that are alive, indexed by their serial numbers.
+static KEYS: Mutex<BTreeMap<KeySerial, Weak<Key>>> = Mutex::new(BTreeMap::new());
+
+/// The lock that serializes the links of keyrings to keyrings.
+static LINK_LOCK: Mutex<()> = Mutex::new(());
+
+/// The keys and the bytes charged to each user, indexed by the UIDs.
+static QUOTAS: Mutex<BTreeMap<u32, Quota>> = Mutex::new(BTreeMap::new());
+
+/// The type of a key, which determines its payload and its operations.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum KeyType {
+    /// A keyring, whose payload is a set of links to other keys.
+    Keyring,
+    /// A key whose payload is a blob that can be read by the user space.
+    User,
+    /// A key whose payload is a blob that can only be used by the kernel (e.g., the file
+    /// system encryption keys).
+    Logon,
+}
+
+impl KeyType {
+    /// Looks up a key type by its name.
+    pub fn from_name(name: &str) -> Result<Self> {
+        match name {
+            "keyring" => Ok(Self::Keyring),
+            "user" => Ok(Self::User),
+            "logon" => Ok(Self::Logon),
+            _ if name.starts_with('.') => {
+                return_errno_with_message!(Errno::EPERM, "the key type is internal")
+            }
+            _ => return_errno_with_message!(Errno::ENODEV, "the key type does not exist"),
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Keyring => "keyring",
+            Self::User => "user",
+            Self::Logon => "logon",
+        }
+    }
+
+    /// Returns the default permissions of the keys of the type that are added by the user space.
+    ///
+    /// Like Linux, the possessor is granted all the permissions that are supported by the type,
+    /// and the owner can only view the key.
+    pub fn default_perm(self) -> KeyPerms {
+        let possessor = match self {
+            Self::Keyring | Self::User => KeyPerm::all(),
+            Self::Logon => KeyPerm::all() - KeyPerm::READ,
+        };
+        KeyPerms::new(possessor, KeyPerm::VIEW, KeyPerm::empty(), KeyPerm::empty())
+    }
+}
+
+bitflags! {
+    /// The permissions of a key for one class of users.
+    pub struct KeyPerm: u32 {
+        /// Viewing the attributes (e.g., with `KEYCTL_DESCRIBE`).
+        const VIEW    = 0x01;
+        /// Reading the payload, or listing the links of a keyring.
+        const READ    = 0x02;
+        /// Updating the payload, or adding and removing the links of a keyring.
+        const WRITE   = 0x04;
+        /// Finding the key by searching the keyrings, or searching a keyring.
+        const SEARCH  = 0x08;
+        /// Linking the key to a keyring.
+        const LINK    = 0x10;
+        /// Changing the attributes (e.g., the owner, the permissions, and the timeout).
+        const SETATTR = 0x20;
+    }
+}
+
+/// The permissions of a key for all the classes of users.
+///
+/// Like Linux, the permissions of the possessor, the owner, the group and the others occupy
+/// the bytes from the highest to the lowest.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct KeyPerms(u32);
+
+impl KeyPerms {
+    const POSSESSOR_SHIFT: u32 = 24;
+    const USER_SHIFT: u32 = 16;
+    const GROUP_SHIFT: u32 = 8;
+    const OTHER_SHIFT: u32 = 0;
+
+    pub const fn new(possessor: KeyPerm, user: KeyPerm, group: KeyPerm, other: KeyPerm) -> Self {
+        Self(
+            (possessor.bits() << Self::POSSESSOR_SHIFT)
+                | (user.bits() << Self::USER_SHIFT)
+                | (group.bits() << Self::GROUP_SHIFT)
+                | (other.bits() << Self::OTHER_SHIFT),
+        )
+    }
+
+    /// Creates the permissions from the raw value, failing with `EINVAL` if the value has
+    /// unknown bits.
+    pub fn from_bits(bits: u32) -> Result<Self> {
+        let all = Self::new(
+            KeyPerm::all(),
+            KeyPerm::all(),
+            KeyPerm::all(),
+            KeyPerm::all(),
+        );
+        if bits & !all.0 != 0 {
+            return_errno_with_message!(Errno::EINVAL, "the key permissions are invalid");
+        }
+        Ok(Self(bits))
+    }
+
+    pub fn bits(self) -> u32 {
+        self.0
+    }
+
+    fn get(self, shift: u32) -> KeyPerm {
+        KeyPerm::from_bits_truncate(self.0 >> shift)
+    }
+}
+
+/// A key, which holds a payload for the kernel or the user space.
+///
+/// A key is alive as long as it is linked to a keyring or referenced by a thread. It is looked
+/// up by its serial number, and the accesses to it are controlled by its [`KeyPerms`].
+pub struct Key {
+    serial: KeySerial,
+    type_: KeyType,
+    description: String,
+    inner: Mutex<KeyInner>,
+}
+
+struct KeyInner {
+    uid: Uid,
+    gid: Gid,
+    perms: KeyPerms,
+    payload: Payload,
+    state: KeyState,
+    /// The time when the key expires, measured by the monotonic clock.
+    expiry: Option<Duration>,
+    /// The bytes charged to the quota of the owner.
+    quota_bytes: usize,
+}
+
+enum Payload {
+    Data(Vec<u8>),
+    Keyring(Vec<Arc<Key>>),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum KeyState {
+    Valid,
+    Revoked,
+    Invalidated,
+}
+
+impl Key {
+    /// Creates a key owned by the user and the group.
+    ///
+    /// The key is charged to the quota of the owner, and this method fails with `EDQUOT` if the
+    /// quota is exceeded.
+    pub fn new(
+        type_: KeyType,
+        description: String,
+        payload: Vec<u8>,
+        uid: Uid,
+        gid: Gid,
+        perms: KeyPerms,
+    ) -> Result<Arc<Self>> {
+        if description.is_empty() || description.len() > MAX_DESC_LEN {
+            return_errno_with_message!(Errno::EINVAL, "the description is invalid");
+        }
+        let quota_bytes = description.len() + 1 + payload.len();
+        let payload = match type_ {
+            KeyType::Keyring => {
+                if !payload.is_empty() {
+                    return_errno_with_message!(Errno::EINVAL, "keyrings cannot have payloads");
+                }
+                Payload::Keyring(Vec::new())
+            }
+            KeyType::User | KeyType::Logon => {
+                check_user_payload(&payload)?;
+                Payload::Data(payload)
+            }
+        };
+        // Like Linux, the description of a logon key must start with a service name.
+        if type_ == KeyType::Logon && description.find(':').is_none_or(|pos| pos == 0) {
+            return_errno_with_message!(Errno::EINVAL, "the description has no service prefix");
+        }
+
+        charge_quota(uid, 1, quota_bytes)?;
+
+        let mut keys = KEYS.lock();
+        let serial = loop {
+            let mut bytes = [0; size_of::<u32>()];
+            getrandom(&mut bytes);
+            // Like Linux, the serial numbers are positive and the small ones are reserved.
+            let serial = (u32::from_ne_bytes(bytes) >> 1) as KeySerial;
+            if serial >= 3 && !keys.contains_key(&serial) {
+                break serial;
+            }
+        };
+        let key = Arc::new(Self {
+            serial,
+            type_,
+            description,
+            inner: Mutex::new(KeyInner {
+                uid,
+                gid,
+                perms,
+                payload,
+                state: KeyState::Valid,
+                expiry: None,
+                quota_bytes,
+            }),
+        });
+        keys.insert(serial, Arc::downgrade(&key));
+
+        Ok(key)
+    }
+
+    /// Looks up a key by its serial number.
+    pub fn lookup(serial: KeySerial) -> Option<Arc<Self>> {
+        let key = KEYS.lock().get(&serial)?.upgrade()?;
+        (key.inner.lock().state != KeyState::Invalidated).then_some(key)
+    }
+
+    /// Finds a keyring by its description, which must be searchable by the credentials.
+    pub fn find_keyring(description: &str, credentials: &Credentials<ReadOp>) -> Option<Arc<Self>> {
+        // The keys are not dropped with the lock held, since dropping a key acquires the lock.
+        let keys: Vec<Arc<Key>> = KEYS.lock().values().filter_map(Weak::upgrade).collect();
+        keys.into_iter().find(|key| {
+            key.type_ == KeyType::Keyring
+                && key.description == description
+                && key.check_valid().is_ok()
+                && key.permits(credentials, false, KeyPerm::SEARCH)
+        })
+    }
+
+    pub fn serial(&self) -> KeySerial {
+        self.serial
+    }
+
+    pub fn type_(&self) -> KeyType {
+        self.type_
+    }
+
+    pub fn description(&self) -> &str {
+        &self.description
+    }
+
+    /// Checks whether the key can be used, i.e., it has not been revoked, invalidated or expired.
+    pub fn check_valid(&self) -> Result<()> {
+        self.inner.lock().check_valid()
+    }
+
+    /// Returns whether the credentials are granted the permissions.
+    ///
+    /// `possessed` is true if the key is possessed by the thread, in which case the permissions
+    /// of the possessor are granted in addition to the ones of the owner, the group or others.
+    pub fn permits(
+        &self,
+        credentials: &Credentials<ReadOp>,
+        possessed: bool,
+        perm: KeyPerm,
+    ) -> bool {
+        let inner = self.inner.lock();
+
+        let fsgid = credentials.fsgid();
+        let mut granted = if credentials.fsuid() == inner.uid {
+            inner.perms.get(KeyPerms::USER_SHIFT)
+        } else if inner.gid != INVALID_GID
+            && (fsgid == inner.gid || credentials.groups().contains(&inner.gid))
+        {
+            inner.perms.get(KeyPerms::GROUP_SHIFT)
+        } else {
+            inner.perms.get(KeyPerms::OTHER_SHIFT)
+        };
+        if possessed {
+            granted |= inner.perms.get(KeyPerms::POSSESSOR_SHIFT);
+        }
+
+        granted.contains(perm)
+    }
+
+    /// Checks whether the credentials are granted the permissions, failing with `EACCES` if not.
+    pub fn check_perm(
+        &self,
+        credentials: &Credentials<ReadOp>,
+        possessed: bool,
+        perm: KeyPerm,
+    ) -> Result<()> {
+        if !self.permits(credentials, possessed, perm) {
+            return_errno_with_message!(Errno::EACCES, "the key permission is denied");
+        }
+        Ok(())
+    }
+
+    /// Returns the description of the attributes in the format of `KEYCTL_DESCRIBE`.
+    pub fn describe(&self) -> String {
+        let inner = self.inner.lock();
+        format!(
+            "{};{};{};{:08x};{}",
+            self.type_.name(),
+            u32::from(inner.uid) as i32,
+            u32::from(inner.gid) as i32,
+            inner.perms.bits(),
+            self.description
+        )
+    }
+
+    /// Reads the payload.
+    ///
+    /// The payload of a keyring is the serial numbers of the linked keys.
+    pub fn read(&self) -> Result<Vec<u8>> {
+        let inner = self.inner.lock();
+        inner.check_valid()?;
+
+        match &inner.payload {
+            Payload::Data(_) if self.type_ == KeyType::Logon => {
+                return_errno_with_message!(Errno::EOPNOTSUPP, "logon keys cannot be read")
+            }
+            Payload::Data(data) => Ok(data.clone()),
+            Payload::Keyring(links) => Ok(links
+                .iter()
+                .flat_map(|key| key.serial.to_ne_bytes())
+                .collect()),
+        }
+    }
+
+    /// Returns the payload of a user key or a logon key for the kernel users.
+    ///
+    /// Unlike [`Self::read`], the payload of a logon key can be returned, so the keys that
+    /// cannot be read by the user space can still be used by the kernel (e.g., to encrypt the
+    /// files).
+    pub fn payload(&self) -> Result<Vec<u8>> {
+        let inner = self.inner.lock();
+        inner.check_valid()?;
+
+        match &inner.payload {
+            Payload::Data(data) => Ok(data.clone()),
+            Payload::Keyring(_) => {
+                return_errno_with_message!(Errno::EOPNOTSUPP, "keyrings have no payloads")
+            }
+        }
+    }
+
+    /// Replaces the payload.
+    pub fn update(&self, payload: Vec<u8>) -> Result<()> {
+        if self.type_ == KeyType::Keyring {
+            return_errno_with_message!(Errno::EOPNOTSUPP, "keyrings cannot be updated");
+        }
+        check_user_payload(&payload)?;
+
+        let mut inner = self.inner.lock();
+        inner.check_valid()?;
+
+        let quota_bytes = self.description.len() + 1 + payload.len();
+        if quota_bytes > inner.quota_bytes {
+            charge_quota(inner.uid, 0, quota_bytes - inner.quota_bytes)?;
+        } else {
+            uncharge_quota(inner.uid, 0, inner.quota_bytes - quota_bytes);
+        }
+        inner.quota_bytes = quota_bytes;
+        inner.payload = Payload::Data(payload);
+
+        Ok(())
+    }
+
+    /// Revokes the key, so that it can no longer be used.
+    ///
+    /// Like Linux, the links of a revoked keyring are removed.
+    pub fn revoke(&self) {
+        self.kill(KeyState::Revoked);
+    }
+
+    /// Invalidates the key, so that it can no longer be found.
+    ///
+    /// Unlike Linux, the key is removed from the keyrings lazily, when the keyrings are searched
+    /// or read.
+    pub fn invalidate(&self) {
+        self.kill(KeyState::Invalidated);
+    }
+
+    fn kill(&self, state: KeyState) {
+        let links = {
+            let mut inner = self.inner.lock();
+            if inner.state != KeyState::Invalidated {
+                inner.state = state;
+            }
+            match &mut inner.payload {
+                Payload::Keyring(links) => core::mem::take(links),
+                Payload::Data(_) => Vec::new(),
+            }
+        };
+        // The links are dropped without the lock held, since they may be the last references.
+        drop(links);
+    }
+
+    /// Sets the timeout after which the key expires, or clears it if `timeout` is `None`.
+    pub fn set_timeout(&self, timeout: Option<Duration>) -> Result<()> {
+        let mut inner = self.inner.lock();
+        inner.check_valid()?;
+        inner.expiry = timeout.map(|timeout| MonotonicClock::get().read_time() + timeout);
+        Ok(())
+    }
+
+    /// Changes the owner and the group of the key.
+    ///
+    /// Like Linux, changing the owner requires `CAP_SYS_ADMIN`, and the owner can only change
+    /// the group to one of its groups. The key is charged to the quota of the new owner.
+    pub fn chown(
+        &self,
+        uid: Option<Uid>,
+        gid: Option<Gid>,
+        credentials: &Credentials<ReadOp>,
+    ) -> Result<()> {
+        let is_admin = credentials.effective_capset().contains(CapSet::SYS_ADMIN);
+
+        let mut inner = self.inner.lock();
+        inner.check_valid()?;
+
+        if let Some(uid) = uid
+            && uid != inner.uid
+            && !is_admin
+        {
+            return_errno_with_message!(Errno::EACCES, "changing the owner requires CAP_SYS_ADMIN");
+        }
+        if let Some(gid) = gid
+            && gid != inner.gid
+            && !is_admin
+            && (credentials.fsuid() != inner.uid
+                || (credentials.fsgid() != gid && !credentials.groups().contains(&gid)))
+        {
+            return_errno_with_message!(Errno::EACCES, "the group cannot be changed");
+        }
+
+        if let Some(uid) = uid
+            && uid != inner.uid
+        {
+            charge_quota(uid, 1, inner.quota_bytes)?;
+            uncharge_quota(inner.uid, 1, inner.quota_bytes);
+            inner.uid = uid;
+        }
+        if let Some(gid) = gid {
+            inner.gid = gid;
+        }
+
+        Ok(())
+    }
+
+    /// Changes the permissions of the key.
+    ///
+    /// Like Linux, only the owner or a thread with `CAP_SYS_ADMIN` can change the permissions.
+    pub fn set_perms(&self, perms: KeyPerms, credentials: &Credentials<ReadOp>) -> Result<()> {
+        let mut inner = self.inner.lock();
+        inner.check_valid()?;
+
+        if credentials.fsuid() != inner.uid
+            && !credentials.effective_capset().contains(CapSet::SYS_ADMIN)
+        {
+            return_errno_with_message!(Errno::EACCES, "the key is not owned by the thread");
+        }
+        inner.perms = perms;
+
+        Ok(())
+    }
+
+    /// Returns the keys linked to the keyring.
+    ///
+    /// This method fails with `ENOTDIR` if the key is not a keyring.
+    pub fn links(&self) -> Result<Vec<Arc<Key>>> {
+        let mut inner = self.inner.lock();
+        inner.check_valid()?;
+
+        let Payload::Keyring(links) = &mut inner.payload else {
+            return_errno_with_message!(Errno::ENOTDIR, "the key is not a keyring");
+        };
+        // The invalidated keys are removed lazily.
+        let (invalidated, valid): (Vec<_>, Vec<_>) = core::mem::take(links)
+            .into_iter()
+            .partition(|key| key.inner.lock().state == KeyState::Invalidated);
+        *links = valid;
+        let links = links.clone();
+        drop(inner);
+        drop(invalidated);
+
+        Ok(links)
+    }
+
+    /// Links the key to the keyring.
+    ///
+    /// Like Linux, a link to another key of the same type and description is replaced. This
+    /// method fails with `EDEADLK` if linking a keyring would create a cycle.
+    pub fn link(&self, key: &Arc<Key>) -> Result<()> {
+        if self.type_ != KeyType::Keyring {
+            return_errno_with_message!(Errno::ENOTDIR, "the key is not a keyring");
+        }
+        // The cycle is checked with the lock held, so that no cycle is created by linking two
+        // keyrings to each other concurrently.
+        let _guard = LINK_LOCK.lock();
+        if key.type_ == KeyType::Keyring && (key.serial == self.serial || key.reaches(self)) {
+            return_errno_with_message!(Errno::EDEADLK, "linking the keyring creates a cycle");
+        }
+
+        let mut inner = self.inner.lock();
+        inner.check_valid()?;
+        let Payload::Keyring(links) = &mut inner.payload else {
+            unreachable!("the payload of a keyring is not a keyring");
+        };
+
+        let replaced = match links
+            .iter()
+            .position(|link| link.type_ == key.type_ && link.description == key.description)
+        {
+            Some(index) => Some(core::mem::replace(&mut links[index], key.clone())),
+            None => {
+                links.push(key.clone());
+                None
+            }
+        };
+        drop(inner);
+        drop(replaced);
+
+        Ok(())
+    }
+
+    /// Unlinks the key from the keyring.
+    ///
+    /// This method fails with `ENOENT` if the key is not linked to the keyring.
+    pub fn unlink(&self, key: &Key) -> Result<()> {
+        let mut inner = self.inner.lock();
+        inner.check_valid()?;
+        let Payload::Keyring(links) = &mut inner.payload else {
+            return_errno_with_message!(Errno::ENOTDIR, "the key is not a keyring");
+        };
+
+        let Some(index) = links.iter().position(|link| link.serial == key.serial) else {
+            return_errno_with_message!(Errno::ENOENT, "the key is not linked to the keyring");
+        };
+        let unlinked = links.remove(index);
+        drop(inner);
+        drop(unlinked);
+
+        Ok(())
+    }
+
+    /// Removes all the links of the keyring.
+    pub fn clear(&self) -> Result<()> {
+        let mut inner = self.inner.lock();
+        inner.check_valid()?;
+        let Payload::Keyring(links) = &mut inner.payload else {
+            return_errno_with_message!(Errno::ENOTDIR, "the key is not a keyring");
+        };
+
+        let links = core::mem::take(links);
+        drop(inner);
+        drop(links);
+
+        Ok(())
+    }
+
+    /// Searches the keyring and its nested keyrings for a key of the type and description.
+    ///
+    /// `possessed` is true if the keyring is possessed by the thread, in which case the keys
+    /// found in it are also possessed. Only the keys and the nested keyrings that grant
+    /// [`KeyPerm::SEARCH`] are searched. Like Linux, the matching keys that are valid are
+    /// preferred, and if only revoked or expired ones are found, this method fails with
+    /// `EKEYREVOKED` or `EKEYEXPIRED`.
+    pub fn search(
+        &self,
+        type_: KeyType,
+        description: &str,
+        credentials: &Credentials<ReadOp>,
+        possessed: bool,
+    ) -> Result<Arc<Key>> {
+        let mut error = Error::with_message(Errno::ENOKEY, "the key is not found");
+        self.search_nested(
+            &|key| key.type_ == type_ && key.description == description,
+            credentials,
+            possessed,
+            0,
+            &mut error,
+        )
+        .ok_or(error)
+    }
+
+    /// Returns whether the key is reachable from the keyring by searching it.
+    pub(super) fn search_for(
+        &self,
+        target: &Key,
+        credentials: &Credentials<ReadOp>,
+        possessed: bool,
+    ) -> bool {
+        let mut error = Error::new(Errno::ENOKEY);
+        self.search_nested(
+            &|key| key.serial == target.serial,
+            credentials,
+            possessed,
+            0,
+            &mut error,
+        )
+        .is_some()
+    }
+
+    fn search_nested(
+        &self,
+        matches: &dyn Fn(&Key) -> bool,
+        credentials: &Credentials<ReadOp>,
+        possessed: bool,
+        depth: usize,
+        error: &mut Error,
+    ) -> Option<Arc<Key>> {
+        let links = self.links().ok()?;
+
+        // The keys in the keyring are searched before the nested keyrings.
+        for key in links.iter() {
+            if !matches(key) || !key.permits(credentials, possessed, KeyPerm::SEARCH) {
+                continue;
+            }
+            match key.check_valid() {
+                Ok(()) => return Some(key.clone()),
+                Err(err) => *error = err,
+            }
+        }
+
+        if depth + 1 >= MAX_SEARCH_DEPTH {
+            return None;
+        }
+        links.iter().find_map(|key| {
+            if key.type_ != KeyType::Keyring
+                || !key.permits(credentials, possessed, KeyPerm::SEARCH)
+            {
+                return None;
+            }
+            key.search_nested(matches, credentials, possessed, depth + 1, error)
+        })
+    }
+
+    /// Returns whether the keyring is reachable from this keyring.
+    fn reaches(&self, target: &Key) -> bool {
+        let Ok(links) = self.links() else {
+            return false;
+        };
+        links.iter().any(|key| {
+            key.type_ == KeyType::Keyring && (key.serial == target.serial || key.reaches(target))
+        })
+    }
+}
+
+impl Debug for Key {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("Key")
+            .field("serial", &self.serial)
+            .field("type_", &self.type_)
+            .field("description", &self.description)
+            .finish_non_exhaustive()
+    }
+}
+
+impl Drop for Key {
+    fn drop(&mut self) {
+        KEYS.lock().remove(&self.serial);
+
+        let inner = self.inner.get_mut();
+        uncharge_quota(inner.uid, 1, inner.quota_bytes);
+    }
+}
+
+impl KeyInner {
+    fn check_valid(&self) -> Result<()> {
+        match self.state {
+            KeyState::Valid => (),
+            KeyState::Revoked => {
+                return_errno_with_message!(Errno::EKEYREVOKED, "the key has been revoked")
+            }
+            KeyState::Invalidated => {
+                return_errno_with_message!(Errno::ENOKEY, "the key has been invalidated")
+            }
+        }
+        if let Some(expiry) = self.expiry
+            && MonotonicClock::get().read_time() >= expiry
+        {
+            return_errno_with_message!(Errno::EKEYEXPIRED, "the key has expired");
+        }
+        Ok(())
+    }
+}
+
+fn check_user_payload(payload: &[u8]) -> Result<()> {
+    if payload.is_empty() || payload.len() > MAX_USER_PAYLOAD_LEN {
+        return_errno_with_message!(Errno::EINVAL, "the payload size is invalid");
+    }
+    Ok(())
+}
+
+/// The keys and the bytes owned by a user.
+#[derive(Default)]
+struct Quota {
+    keys: usize,
+    bytes: usize,
+}
+
+impl Quota {
+    // Like Linux, the quotas of the root user are much larger than the ones of other users.
+    const MAX_KEYS: usize = 200;
+    const MAX_BYTES: usize = 20000;
+    const MAX_ROOT_KEYS: usize = 1000000;
+    const MAX_ROOT_BYTES: usize = 25000000;
+}
+
+fn charge_quota(uid: Uid, keys: usize, bytes: usize) -> Result<()> {
+    let (max_keys, max_bytes) = if uid.is_root() {
+        (Quota::MAX_ROOT_KEYS, Quota::MAX_ROOT_BYTES)
+    } else {
+        (Quota::MAX_KEYS, Quota::MAX_BYTES)
+    };
+
+    let mut quotas = QUOTAS.lock();
+    let quota = quotas.entry(uid.into()).or_default();
+    if quota.keys + keys > max_keys || quota.bytes + bytes > max_bytes {
+        return_errno_with_message!(Errno::EDQUOT, "the key quota is exceeded");
+    }
+    quota.keys += keys;
+    quota.bytes += bytes;
+
+    Ok(())
+}
+
+fn uncharge_quota(uid: Uid, keys: usize, bytes: usize) {
+    let mut quotas = QUOTAS.lock();
+    let quota = quotas.get_mut(&u32::from(uid)).unwrap();
+    quota.keys -= keys;
+    quota.bytes -= bytes;
+    if quota.keys == 0 {
+        quotas.remove(&u32::from(uid));
+    }
+}
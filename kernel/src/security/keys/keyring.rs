@@ -0,0 +1,301 @@
+// SPDX-License-Identifier: MPL-2.0
+
+use aster_rights::ReadOp;
+
+use super::key::{Key, KeyPerm, KeyPerms, KeySerial, KeyType, INVALID_GID};
+use crate::{
+    prelude::*,
+    process::{posix_thread::PosixThread, Credentials, Uid},
+};
+
+// The special serial numbers that refer to the keyrings of the current thread.
+const THREAD_KEYRING: KeySerial = -1;
+const PROCESS_KEYRING: KeySerial = -2;
+const SESSION_KEYRING: KeySerial = -3;
+const USER_KEYRING: KeySerial = -4;
+const USER_SESSION_KEYRING: KeySerial = -5;
+const GROUP_KEYRING: KeySerial = -6;
+const REQKEY_AUTH_KEY: KeySerial = -7;
+const REQUESTOR_KEYRING: KeySerial = -8;
+
+/// The user keyrings and the user session keyrings, indexed by the UIDs.
+static USER_KEYRINGS: Mutex<BTreeMap<u32, UserKeyrings>> = Mutex::new(BTreeMap::new());
+
+/// The keyrings of a thread.
+///
+/// Like Linux, the thread keyring is private to the thread, the process keyring is shared by
+/// the threads in the process, and the session keyring is inherited by the child threads and
+/// processes. The thread and process keyrings are discarded on `execve`, while the session
+/// keyring is preserved. All the keyrings are created on demand.
+pub struct ThreadKeyrings {
+    thread: Option<Arc<Key>>,
+    process: Arc<Mutex<Option<Arc<Key>>>>,
+    session: Option<Arc<Key>>,
+}
+
+impl ThreadKeyrings {
+    /// Creates the keyrings of the init process, which are all empty.
+    pub fn new() -> Self {
+        Self {
+            thread: None,
+            process: Arc::new(Mutex::new(None)),
+            session: None,
+        }
+    }
+
+    /// Returns the keyrings of a new thread in the same process.
+    pub fn new_thread(&self) -> Self {
+        Self {
+            thread: None,
+            process: self.process.clone(),
+            session: self.session.clone(),
+        }
+    }
+
+    /// Returns the keyrings of the main thread in a new child process.
+    pub fn new_process(&self) -> Self {
+        Self {
+            thread: None,
+            process: Arc::new(Mutex::new(None)),
+            session: self.session.clone(),
+        }
+    }
+
+    /// Discards the thread and process keyrings when the thread calls `execve`.
+    pub fn reset_on_exec(&mut self) {
+        self.thread = None;
+        self.process = Arc::new(Mutex::new(None));
+    }
+
+    /// Returns the keyrings that are searched for the keys requested by the thread.
+    ///
+    /// Like Linux, the user session keyring is searched if the thread has no session keyring.
+    fn searched(&self, uid: Uid) -> Result<Vec<Arc<Key>>> {
+        let mut keyrings = Vec::with_capacity(3);
+        keyrings.extend(self.thread.clone());
+        keyrings.extend(self.process.lock().clone());
+        match &self.session {
+            Some(session) => keyrings.push(session.clone()),
+            None => keyrings.push(UserKeyrings::get(uid)?.session),
+        }
+        Ok(keyrings)
+    }
+}
+
+impl Default for ThreadKeyrings {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[derive(Clone)]
+struct UserKeyrings {
+    user: Arc<Key>,
+    session: Arc<Key>,
+}
+
+impl UserKeyrings {
+    /// Returns the keyrings of the user, creating them if they do not exist.
+    fn get(uid: Uid) -> Result<Self> {
+        let uid_raw = u32::from(uid);
+        let mut user_keyrings = USER_KEYRINGS.lock();
+        if let Some(keyrings) = user_keyrings.get(&uid_raw) {
+            return Ok(keyrings.clone());
+        }
+
+        // Like Linux, the user keyrings are not owned by any group, and the user session
+        // keyring is linked to the user keyring.
+        let perms = KeyPerms::new(
+            KeyPerm::all(),
+            KeyPerm::all(),
+            KeyPerm::empty(),
+            KeyPerm::empty(),
+        );
+        let new_keyring = |description| {
+            Key::new(
+                KeyType::Keyring,
+                description,
+                Vec::new(),
+                uid,
+                INVALID_GID,
+                perms,
+            )
+        };
+        let user = new_keyring(format!("_uid.{}", uid_raw))?;
+        let session = new_keyring(format!("_uid_ses.{}", uid_raw))?;
+        session.link(&user)?;
+
+        let keyrings = Self { user, session };
+        user_keyrings.insert(uid_raw, keyrings.clone());
+        Ok(keyrings)
+    }
+}
+
+/// A reference to a key that is looked up by a thread.
+pub struct KeyRef {
+    key: Arc<Key>,
+    possessed: bool,
+}
+
+impl KeyRef {
+    pub fn key(&self) -> &Arc<Key> {
+        &self.key
+    }
+
+    /// Returns whether the key is possessed by the thread.
+    pub fn is_possessed(&self) -> bool {
+        self.possessed
+    }
+
+    /// Checks whether the thread is granted the permissions, failing with `EACCES` if not.
+    pub fn check_perm(&self, credentials: &Credentials<ReadOp>, perm: KeyPerm) -> Result<()> {
+        self.key.check_perm(credentials, self.possessed, perm)
+    }
+}
+
+bitflags! {
+    /// The flags that control how a key is looked up.
+    pub struct LookupFlags: u32 {
+        /// Creates the keyring if the serial number refers to a keyring of the thread that does
+        /// not exist.
+        const CREATE = 1 << 0;
+        /// Allows the key to be invalid (e.g., revoked or expired), so that it can be unlinked.
+        const FOR_UNLINK = 1 << 1;
+    }
+}
+
+/// Looks up a key for the thread by its serial number, which can also be one of the special
+/// serial numbers that refer to the keyrings of the thread.
+///
+/// The keyrings of the thread that are referred to by the special serial numbers are always
+/// possessed. Other keys are possessed if they can be found by searching the keyrings of the
+/// thread.
+pub fn lookup_key(serial: KeySerial, flags: LookupFlags, thread: &PosixThread) -> Result<KeyRef> {
+    let credentials = thread.credentials();
+    let create = flags.contains(LookupFlags::CREATE);
+    let mut keyrings = thread.keyrings().lock();
+
+    let new_keyring = |description: &str| {
+        Key::new(
+            KeyType::Keyring,
+            description.to_string(),
+            Vec::new(),
+            credentials.fsuid(),
+            credentials.fsgid(),
+            KeyPerms::new(
+                KeyPerm::all(),
+                KeyPerm::VIEW,
+                KeyPerm::empty(),
+                KeyPerm::empty(),
+            ),
+        )
+    };
+    let get_or_create = |keyring: &mut Option<Arc<Key>>, description: &str| -> Result<Arc<Key>> {
+        if keyring.is_none() {
+            if !create {
+                return_errno_with_message!(Errno::ENOKEY, "the keyring does not exist");
+            }
+            *keyring = Some(new_keyring(description)?);
+        }
+        Ok(keyring.clone().unwrap())
+    };
+
+    let (key, possessed) = match serial {
+        THREAD_KEYRING => (get_or_create(&mut keyrings.thread, "_tid")?, true),
+        PROCESS_KEYRING => (get_or_create(&mut *keyrings.process.lock(), "_pid")?, true),
+        SESSION_KEYRING => {
+            // Like Linux, a session keyring is always installed when it is accessed.
+            if keyrings.session.is_none() {
+                keyrings.session = if create {
+                    Some(new_session_keyring("_ses".to_string(), &credentials)?)
+                } else {
+                    Some(UserKeyrings::get(credentials.ruid())?.session)
+                };
+            }
+            (keyrings.session.clone().unwrap(), true)
+        }
+        USER_KEYRING => (UserKeyrings::get(credentials.ruid())?.user, true),
+        USER_SESSION_KEYRING => (UserKeyrings::get(credentials.ruid())?.session, true),
+        GROUP_KEYRING => {
+            return_errno_with_message!(Errno::EINVAL, "group keyrings are not supported")
+        }
+        REQKEY_AUTH_KEY | REQUESTOR_KEYRING => {
+            return_errno_with_message!(Errno::ENOKEY, "the thread is not instantiating a key")
+        }
+        serial if serial > 0 => {
+            let key = Key::lookup(serial)
+                .ok_or_else(|| Error::with_message(Errno::ENOKEY, "the key does not exist"))?;
+            let possessed = keyrings
+                .searched(credentials.ruid())?
+                .iter()
+                .any(|keyring| {
+                    keyring.serial() == key.serial() || keyring.search_for(&key, &credentials, true)
+                });
+            (key, possessed)
+        }
+        _ => return_errno_with_message!(Errno::EINVAL, "the special key ID is invalid"),
+    };
+    drop(keyrings);
+
+    if !flags.contains(LookupFlags::FOR_UNLINK) {
+        key.check_valid()?;
+    }
+
+    Ok(KeyRef { key, possessed })
+}
+
+/// Searches the keyrings of the thread for a key of the type and description.
+///
+/// The keys found in the keyrings are possessed by the thread. Unlike [`Key::search`], this
+/// function fails with `ENOKEY` if only revoked or expired keys are found, like `request_key`
+/// in Linux.
+pub fn search_thread_keyrings(
+    type_: KeyType,
+    description: &str,
+    thread: &PosixThread,
+) -> Result<Arc<Key>> {
+    let credentials = thread.credentials();
+    let keyrings = thread.keyrings().lock().searched(credentials.ruid())?;
+
+    keyrings
+        .iter()
+        .filter(|keyring| keyring.permits(&credentials, true, KeyPerm::SEARCH))
+        .find_map(|keyring| keyring.search(type_, description, &credentials, true).ok())
+        .ok_or_else(|| Error::with_message(Errno::ENOKEY, "the key is not found"))
+}
+
+/// Joins the session keyring of the name, or a new anonymous session keyring if `name` is
+/// `None`, returning the serial number of the keyring.
+///
+/// Like Linux, a new keyring is created if no keyring of the name can be found.
+pub fn join_session_keyring(name: Option<String>, thread: &PosixThread) -> Result<KeySerial> {
+    let credentials = thread.credentials();
+
+    let keyring = match name {
+        None => new_session_keyring("_ses".to_string(), &credentials)?,
+        Some(name) => match Key::find_keyring(&name, &credentials) {
+            Some(keyring) => keyring,
+            None => new_session_keyring(name, &credentials)?,
+        },
+    };
+    let serial = keyring.serial();
+    thread.keyrings().lock().session = Some(keyring);
+
+    Ok(serial)
+}
+
+fn new_session_keyring(description: String, credentials: &Credentials<ReadOp>) -> Result<Arc<Key>> {
+    Key::new(
+        KeyType::Keyring,
+        description,
+        Vec::new(),
+        credentials.fsuid(),
+        credentials.fsgid(),
+        KeyPerms::new(
+            KeyPerm::all(),
+            KeyPerm::VIEW | KeyPerm::READ | KeyPerm::LINK,
+            KeyPerm::empty(),
+            KeyPerm::empty(),
+        ),
+    )
+}
@@ -0,0 +1,28 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! The key retention service, which retains the keys (e.g., the authentication tokens and the
+//! encryption keys) in the kernel for the file systems and the user-space services.
+//!
+//! A key has a type, a description, and a payload. The user keys can be read by the user space,
+//! while the logon keys can only be used by the kernel. The keyrings are the keys that link to
+//! other keys. The user space adds keys to keyrings with `add_key`, finds keys with
+//! `request_key`, and manages them with `keyctl`.
+//!
+//! Each thread has a thread keyring, a process keyring and a session keyring (see
+//! [`ThreadKeyrings`]), and each user has a user keyring and a user session keyring. A thread
+//! _possesses_ the keys that can be found by searching its keyrings, and it is granted the
+//! possessor permissions of these keys in addition to the permissions of the owner, the group or
+//! the others.
+//!
+//! Unlike Linux, keys cannot be requested from the user space with `/sbin/request-key`, so
+//! `request_key` only finds the keys that already exist.
+//!
+//! For more details, see <https://man7.org/linux/man-pages/man7/keyrings.7.html>.
+
+mod key;
+mod keyring;
+
+pub use key::{Key, KeyPerm, KeyPerms, KeySerial, KeyType, MAX_DESC_LEN};
+pub use keyring::{
+    join_session_keyring, lookup_key, search_thread_keyrings, KeyRef, LookupFlags, ThreadKeyrings,
+};
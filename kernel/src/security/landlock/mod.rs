@@ -0,0 +1,274 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! Landlock, which allows unprivileged processes to restrict their own accesses.
+//!
+//! A process creates a ruleset that handles some access rights with `landlock_create_ruleset`,
+//! adds the rules that allow some of the accesses with `landlock_add_rule`, and then enforces the
+//! ruleset on itself with `landlock_restrict_self`. The enforced rulesets become the layers of the
+//! domain of the thread, which is inherited by the child threads and processes, and is preserved
+//! across `execve`. An access is allowed only if it is allowed by all the layers, so the domain
+//! can never be relaxed.
+//!
+//! Like Linux, [`AccessFs::REFER`] is always handled by the layers that handle any access right
+//! of the file system, so that the files cannot be linked or renamed to other directories unless
+//! it is explicitly allowed. Unlike Linux, the access rights of the files are not compared when
+//! they are linked or renamed.
+//!
+//! For more details, see <https://docs.kernel.org/userspace-api/landlock.html>.
+
+mod ruleset;
+
+pub use ruleset::{AccessFs, AccessNet, Ruleset, RulesetFile};
+
+use super::SecurityModule;
+use crate::{
+    fs::{
+        path::Dentry,
+        utils::{AccessMode, InodeType},
+    },
+    prelude::*,
+    process::posix_thread::PosixThread,
+    util::net::Protocol,
+};
+
+/// The version of the Landlock ABI.
+///
+/// The version 4 supports all the access rights of [`AccessFs`] and [`AccessNet`].
+pub const ABI_VERSION: u32 = 4;
+
+/// The maximum number of layers in a domain.
+const MAX_LAYERS: usize = 16;
+
+/// The domain of a thread, which consists of the enforced rulesets.
+#[derive(Debug)]
+pub struct Domain {
+    layers: Vec<Arc<Ruleset>>,
+}
+
+impl Domain {
+    /// Creates a domain by adding the ruleset as a new layer on top of the `prev` domain.
+    ///
+    /// This method fails with `E2BIG` if there are too many layers.
+    pub fn new(prev: Option<&Domain>, ruleset: Ruleset) -> Result<Arc<Self>> {
+        let mut layers = prev.map_or_else(Vec::new, |prev| prev.layers.clone());
+        if layers.len() >= MAX_LAYERS {
+            return_errno_with_message!(Errno::E2BIG, "the domain has too many layers");
+        }
+        layers.push(Arc::new(ruleset));
+
+        Ok(Arc::new(Self { layers }))
+    }
+
+    /// Checks whether the accesses to the file are allowed by all the layers.
+    ///
+    /// An access is allowed by a layer if the layer does not handle it, or if it is allowed by
+    /// the rule of the file or any of its parent directories.
+    fn check_path(&self, dentry: &Dentry, access: AccessFs) -> Result<()> {
+        let mut missing = self
+            .layers
+            .iter()
+            .map(|layer| access & handled_fs(layer))
+            .collect::<Vec<_>>();
+
+        let mut current = dentry.clone();
+        loop {
+            if missing.iter().all(|missing| missing.is_empty()) {
+                return Ok(());
+            }
+            for (layer, missing) in self.layers.iter().zip(missing.iter_mut()) {
+                *missing -= layer.path_rule(&current);
+            }
+
+            match current.effective_parent() {
+                Some(parent) => current = parent,
+                None => break,
+            }
+        }
+
+        if missing.iter().all(|missing| missing.is_empty()) {
+            return Ok(());
+        }
+        return_errno_with_message!(Errno::EACCES, "the access is denied by Landlock");
+    }
+
+    /// Checks whether the files can be linked or renamed from the old directory to the new
+    /// directory.
+    fn check_refer(&self, old_dir: &Dentry, new_dir: &Dentry) -> Result<()> {
+        if ruleset::inode_key(old_dir) == ruleset::inode_key(new_dir) {
+            return Ok(());
+        }
+
+        self.check_path(old_dir, AccessFs::REFER)
+            .and_then(|_| self.check_path(new_dir, AccessFs::REFER))
+            .map_err(|_| {
+                Error::with_message(
+                    Errno::EXDEV,
+                    "moving files to other directories is denied by Landlock",
+                )
+            })
+    }
+
+    /// Checks whether the accesses to the TCP port are allowed by all the layers.
+    fn check_port(&self, port: u16, access: AccessNet) -> Result<()> {
+        let is_allowed = self
+            .layers
+            .iter()
+            .all(|layer| layer.port_rule(port).contains(access & layer.handled_net()));
+        if !is_allowed {
+            return_errno_with_message!(Errno::EACCES, "the access is denied by Landlock");
+        }
+
+        Ok(())
+    }
+}
+
+/// Returns the access rights of the file system that are handled by the layer.
+fn handled_fs(layer: &Ruleset) -> AccessFs {
+    let handled = layer.handled_fs();
+    if handled.is_empty() {
+        handled
+    } else {
+        handled | AccessFs::REFER
+    }
+}
+
+/// The Landlock security module.
+pub(super) struct Landlock;
+
+impl Landlock {
+    fn domain(thread: &PosixThread) -> Option<Arc<Domain>> {
+        thread.landlock_domain().lock().clone()
+    }
+}
+
+impl SecurityModule for Landlock {
+    fn file_open(
+        &self,
+        thread: &PosixThread,
+        dentry: &Dentry,
+        access_mode: AccessMode,
+        truncate: bool,
+    ) -> Result<()> {
+        let Some(domain) = Self::domain(thread) else {
+            return Ok(());
+        };
+
+        let mut access = AccessFs::empty();
+        if dentry.type_() == InodeType::Dir {
+            if access_mode.is_readable() {
+                access |= AccessFs::READ_DIR;
+            }
+        } else {
+            if access_mode.is_readable() {
+                access |= AccessFs::READ_FILE;
+            }
+            if access_mode.is_writable() {
+                access |= AccessFs::WRITE_FILE;
+            }
+            if truncate {
+                access |= AccessFs::TRUNCATE;
+            }
+        }
+
+        domain.check_path(dentry, access)
+    }
+
+    fn file_exec(&self, thread: &PosixThread, dentry: &Dentry) -> Result<()> {
+        let Some(domain) = Self::domain(thread) else {
+            return Ok(());
+        };
+
+        domain.check_path(dentry, AccessFs::EXECUTE)
+    }
+
+    fn path_mknod(&self, thread: &PosixThread, dir: &Dentry, type_: InodeType) -> Result<()> {
+        let Some(domain) = Self::domain(thread) else {
+            return Ok(());
+        };
+
+        domain.check_path(dir, AccessFs::make(type_))
+    }
+
+    fn path_link(&self, thread: &PosixThread, old: &Dentry, new_dir: &Dentry) -> Result<()> {
+        let Some(domain) = Self::domain(thread) else {
+            return Ok(());
+        };
+
+        domain.check_path(new_dir, AccessFs::make(old.type_()))?;
+        if let Some(old_dir) = old.effective_parent() {
+            domain.check_refer(&old_dir, new_dir)?;
+        }
+
+        Ok(())
+    }
+
+    fn path_unlink(&self, thread: &PosixThread, dir: &Dentry, _name: &str) -> Result<()> {
+        let Some(domain) = Self::domain(thread) else {
+            return Ok(());
+        };
+
+        domain.check_path(dir, AccessFs::REMOVE_FILE)
+    }
+
+    fn path_rmdir(&self, thread: &PosixThread, dir: &Dentry, _name: &str) -> Result<()> {
+        let Some(domain) = Self::domain(thread) else {
+            return Ok(());
+        };
+
+        domain.check_path(dir, AccessFs::REMOVE_DIR)
+    }
+
+    fn path_rename(
+        &self,
+        thread: &PosixThread,
+        old_dir: &Dentry,
+        old_name: &str,
+        new_dir: &Dentry,
+        new_name: &str,
+    ) -> Result<()> {
+        let Some(domain) = Self::domain(thread) else {
+            return Ok(());
+        };
+        // If the old file does not exist, the rename operation will fail anyway.
+        let Ok(old) = old_dir.lookup(old_name) else {
+            return Ok(());
+        };
+
+        domain.check_path(old_dir, AccessFs::remove(old.type_()))?;
+        domain.check_path(new_dir, AccessFs::make(old.type_()))?;
+        if let Ok(replaced) = new_dir.lookup(new_name) {
+            domain.check_path(new_dir, AccessFs::remove(replaced.type_()))?;
+        }
+        domain.check_refer(old_dir, new_dir)
+    }
+
+    fn path_truncate(&self, thread: &PosixThread, dentry: &Dentry) -> Result<()> {
+        let Some(domain) = Self::domain(thread) else {
+            return Ok(());
+        };
+
+        domain.check_path(dentry, AccessFs::TRUNCATE)
+    }
+
+    fn socket_bind(&self, thread: &PosixThread, protocol: Protocol, port: u16) -> Result<()> {
+        let Some(domain) = Self::domain(thread) else {
+            return Ok(());
+        };
+        if !matches!(protocol, Protocol::IPPROTO_TCP) {
+            return Ok(());
+        }
+
+        domain.check_port(port, AccessNet::BIND_TCP)
+    }
+
+    fn socket_connect(&self, thread: &PosixThread, protocol: Protocol, port: u16) -> Result<()> {
+        let Some(domain) = Self::domain(thread) else {
+            return Ok(());
+        };
+        if !matches!(protocol, Protocol::IPPROTO_TCP) {
+            return Ok(());
+        }
+
+        domain.check_port(port, AccessNet::CONNECT_TCP)
+    }
+}
@@ -0,0 +1,223 @@
+// SPDX-License-Identifier: MPL-2.0
+
+use crate::{
+    events::IoEvents,
+    fs::{
+        file_handle::FileLike,
+        path::Dentry,
+        utils::{InodeMode, InodeType, Metadata, StatusFlags},
+    },
+    prelude::*,
+    process::{
+        signal::{PollHandle, Pollable},
+        Gid, Uid,
+    },
+    time::clocks::RealTimeClock,
+};
+
+bitflags! {
+    /// The access rights of the file system (`LANDLOCK_ACCESS_FS_*`).
+    pub struct AccessFs: u64 {
+        const EXECUTE     = 1 << 0;
+        const WRITE_FILE  = 1 << 1;
+        const READ_FILE   = 1 << 2;
+        const READ_DIR    = 1 << 3;
+        const REMOVE_DIR  = 1 << 4;
+        const REMOVE_FILE = 1 << 5;
+        const MAKE_CHAR   = 1 << 6;
+        const MAKE_DIR    = 1 << 7;
+        const MAKE_REG    = 1 << 8;
+        const MAKE_SOCK   = 1 << 9;
+        const MAKE_FIFO   = 1 << 10;
+        const MAKE_BLOCK  = 1 << 11;
+        const MAKE_SYM    = 1 << 12;
+        const REFER       = 1 << 13;
+        const TRUNCATE    = 1 << 14;
+    }
+}
+
+impl AccessFs {
+    /// The access rights that can be granted to the files that are not directories.
+    pub const FILE: Self = Self::EXECUTE
+        .union(Self::WRITE_FILE)
+        .union(Self::READ_FILE)
+        .union(Self::TRUNCATE);
+
+    /// Returns the access right to create a file of the type.
+    pub fn make(type_: InodeType) -> Self {
+        match type_ {
+            InodeType::NamedPipe => Self::MAKE_FIFO,
+            InodeType::CharDevice => Self::MAKE_CHAR,
+            InodeType::Dir => Self::MAKE_DIR,
+            InodeType::BlockDevice => Self::MAKE_BLOCK,
+            InodeType::File => Self::MAKE_REG,
+            InodeType::SymLink => Self::MAKE_SYM,
+            InodeType::Socket => Self::MAKE_SOCK,
+        }
+    }
+
+    /// Returns the access right to remove a file of the type.
+    pub fn remove(type_: InodeType) -> Self {
+        if type_ == InodeType::Dir {
+            Self::REMOVE_DIR
+        } else {
+            Self::REMOVE_FILE
+        }
+    }
+}
+
+bitflags! {
+    /// The access rights of the network (`LANDLOCK_ACCESS_NET_*`).
+    pub struct AccessNet: u64 {
+        const BIND_TCP    = 1 << 0;
+        const CONNECT_TCP = 1 << 1;
+    }
+}
+
+/// A set of rules, which becomes a layer of the domain of a thread when it is enforced.
+///
+/// An access that is handled by the ruleset is denied unless it is allowed by a rule. For the
+/// file system, a rule allows the accesses to a file and all the files beneath it (if it is a
+/// directory). For the network, a rule allows the accesses to a TCP port.
+#[derive(Debug, Clone)]
+pub struct Ruleset {
+    handled_fs: AccessFs,
+    handled_net: AccessNet,
+    /// The rules of the file system, which are indexed by the addresses of the inodes.
+    ///
+    /// The rule holds the dentry, so that the inode cannot be freed and its address cannot be
+    /// reused by another inode.
+    fs_rules: BTreeMap<usize, (Dentry, AccessFs)>,
+    net_rules: BTreeMap<u16, AccessNet>,
+}
+
+impl Ruleset {
+    /// Creates an empty ruleset that handles the access rights.
+    pub fn new(handled_fs: AccessFs, handled_net: AccessNet) -> Self {
+        Self {
+            handled_fs,
+            handled_net,
+            fs_rules: BTreeMap::new(),
+            net_rules: BTreeMap::new(),
+        }
+    }
+
+    pub fn handled_fs(&self) -> AccessFs {
+        self.handled_fs
+    }
+
+    pub fn handled_net(&self) -> AccessNet {
+        self.handled_net
+    }
+
+    /// Allows the accesses to the file and all the files beneath it.
+    ///
+    /// This method fails with `EINVAL` if the accesses are not handled by the ruleset, or if
+    /// accesses other than [`AccessFs::FILE`] are allowed for a file that is not a directory.
+    pub fn add_path_rule(&mut self, dentry: Dentry, access: AccessFs) -> Result<()> {
+        if !self.handled_fs.contains(access) {
+            return_errno_with_message!(Errno::EINVAL, "the access rights are not handled");
+        }
+        if dentry.type_() != InodeType::Dir && !AccessFs::FILE.contains(access) {
+            return_errno_with_message!(
+                Errno::EINVAL,
+                "the access rights can only be granted to directories"
+            );
+        }
+
+        let (_, allowed) = self
+            .fs_rules
+            .entry(inode_key(&dentry))
+            .or_insert((dentry, AccessFs::empty()));
+        *allowed |= access;
+        Ok(())
+    }
+
+    /// Allows the accesses to the TCP port.
+    ///
+    /// This method fails with `EINVAL` if the accesses are not handled by the ruleset.
+    pub fn add_port_rule(&mut self, port: u16, access: AccessNet) -> Result<()> {
+        if !self.handled_net.contains(access) {
+            return_errno_with_message!(Errno::EINVAL, "the access rights are not handled");
+        }
+
+        *self.net_rules.entry(port).or_insert(AccessNet::empty()) |= access;
+        Ok(())
+    }
+
+    /// Returns the accesses to the file that are allowed by the rule of the file itself.
+    ///
+    /// The rules of the parent directories are not considered.
+    pub(super) fn path_rule(&self, dentry: &Dentry) -> AccessFs {
+        self.fs_rules
+            .get(&inode_key(dentry))
+            .map_or(AccessFs::empty(), |(_, access)| *access)
+    }
+
+    /// Returns the accesses to the TCP port that are allowed.
+    pub(super) fn port_rule(&self, port: u16) -> AccessNet {
+        self.net_rules
+            .get(&port)
+            .copied()
+            .unwrap_or(AccessNet::empty())
+    }
+}
+
+/// Returns the key of the file in the rules.
+///
+/// Like Linux, the rules are tied to the inodes instead of the paths, so they still work after
+/// the files are renamed, or if the files are accessed by other paths (e.g., bind mounts).
+pub(super) fn inode_key(dentry: &Dentry) -> usize {
+    Arc::as_ptr(dentry.inode()) as *const () as usize
+}
+
+/// The file that refers to a [`Ruleset`], which is created by `landlock_create_ruleset`.
+pub struct RulesetFile {
+    ruleset: Mutex<Ruleset>,
+}
+
+impl RulesetFile {
+    pub fn new(ruleset: Ruleset) -> Self {
+        Self {
+            ruleset: Mutex::new(ruleset),
+        }
+    }
+
+    pub fn ruleset(&self) -> &Mutex<Ruleset> {
+        &self.ruleset
+    }
+}
+
+impl Pollable for RulesetFile {
+    fn poll(&self, _mask: IoEvents, _poller: Option<&mut PollHandle>) -> IoEvents {
+        IoEvents::empty()
+    }
+}
+
+impl FileLike for RulesetFile {
+    fn status_flags(&self) -> StatusFlags {
+        StatusFlags::empty()
+    }
+
+    fn metadata(&self) -> Metadata {
+        // This is a dummy implementation.
+        // TODO: Add "anonymous inode fs" and link `RulesetFile` to it.
+        let now = RealTimeClock::get().read_time();
+        Metadata {
+            dev: 0,
+            ino: 0,
+            size: 0,
+            blk_size: 0,
+            blocks: 0,
+            atime: now,
+            mtime: now,
+            ctime: now,
+            type_: InodeType::NamedPipe,
+            mode: InodeMode::from_bits_truncate(0o600),
+            nlinks: 1,
+            uid: Uid::new_root(),
+            gid: Gid::new_root(),
+            rdev: 0,
+        }
+    }
+}
@@ -0,0 +1,167 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! The security hooks, which allow security modules to restrict the accesses to kernel objects.
+//!
+//! The VFS and the sockets call the hooks before the objects are accessed, after the
+//! discretionary access control checks pass. Each security module can deny the access by
+//! returning an error, in which case the operation fails with the error. Like the Linux
+//! security modules, the hooks are only called for user threads, and the modules are stacked,
+//! i.e., an access is allowed only if it is allowed by all the modules.
+//!
+//! Currently, the only security module is [`landlock`], which allows unprivileged processes to
+//! sandbox themselves.
+//!
+//! The [`audit`] subsystem is not a security module, since it only records the events without
+//! restricting any access. Neither is the key retention service in [`keys`], which retains the
+//! keys for the file systems and the user-space services.
+
+use ostd::task::Task;
+
+use crate::{
+    fs::{
+        path::Dentry,
+        utils::{AccessMode, InodeType},
+    },
+    prelude::*,
+    process::posix_thread::{AsPosixThread, PosixThread},
+    util::net::Protocol,
+};
+
+pub mod audit;
+pub mod keys;
+pub mod landlock;
+
+/// A security module, which implements some of the security hooks.
+///
+/// All the hooks allow the access by default. See the functions of the same names in this
+/// module for the meanings of the hooks.
+trait SecurityModule: Sync {
+    fn file_open(
+        &self,
+        _thread: &PosixThread,
+        _dentry: &Dentry,
+        _access_mode: AccessMode,
+        _truncate: bool,
+    ) -> Result<()> {
+        Ok(())
+    }
+
+    fn file_exec(&self, _thread: &PosixThread, _dentry: &Dentry) -> Result<()> {
+        Ok(())
+    }
+
+    fn path_mknod(&self, _thread: &PosixThread, _dir: &Dentry, _type_: InodeType) -> Result<()> {
+        Ok(())
+    }
+
+    fn path_link(&self, _thread: &PosixThread, _old: &Dentry, _new_dir: &Dentry) -> Result<()> {
+        Ok(())
+    }
+
+    fn path_unlink(&self, _thread: &PosixThread, _dir: &Dentry, _name: &str) -> Result<()> {
+        Ok(())
+    }
+
+    fn path_rmdir(&self, _thread: &PosixThread, _dir: &Dentry, _name: &str) -> Result<()> {
+        Ok(())
+    }
+
+    fn path_rename(
+        &self,
+        _thread: &PosixThread,
+        _old_dir: &Dentry,
+        _old_name: &str,
+        _new_dir: &Dentry,
+        _new_name: &str,
+    ) -> Result<()> {
+        Ok(())
+    }
+
+    fn path_truncate(&self, _thread: &PosixThread, _dentry: &Dentry) -> Result<()> {
+        Ok(())
+    }
+
+    fn socket_bind(&self, _thread: &PosixThread, _protocol: Protocol, _port: u16) -> Result<()> {
+        Ok(())
+    }
+
+    fn socket_connect(&self, _thread: &PosixThread, _protocol: Protocol, _port: u16) -> Result<()> {
+        Ok(())
+    }
+}
+
+/// The enabled security modules, in the order that they are called.
+static MODULES: &[&dyn SecurityModule] = &[&landlock::Landlock];
+
+/// Calls the hook of all the modules for the current thread.
+fn call_hooks(hook: impl Fn(&dyn SecurityModule, &PosixThread) -> Result<()>) -> Result<()> {
+    let Some(task) = Task::current() else {
+        return Ok(());
+    };
+    let Some(posix_thread) = task.as_posix_thread() else {
+        return Ok(());
+    };
+
+    MODULES
+        .iter()
+        .try_for_each(|module| hook(*module, posix_thread))
+}
+
+/// Checks whether the file can be opened with the access mode.
+///
+/// `truncate` is true if the file is truncated when it is opened (i.e., with `O_TRUNC`). This
+/// hook is not called for the files opened with `O_PATH`, since they cannot be accessed.
+pub fn file_open(dentry: &Dentry, access_mode: AccessMode, truncate: bool) -> Result<()> {
+    call_hooks(|module, thread| module.file_open(thread, dentry, access_mode, truncate))
+}
+
+/// Checks whether the file can be executed, either as a program or as its interpreter.
+pub fn file_exec(dentry: &Dentry) -> Result<()> {
+    call_hooks(|module, thread| module.file_exec(thread, dentry))
+}
+
+/// Checks whether a file of the type can be created in the directory.
+pub fn path_mknod(dir: &Dentry, type_: InodeType) -> Result<()> {
+    call_hooks(|module, thread| module.path_mknod(thread, dir, type_))
+}
+
+/// Checks whether a new name of the file can be linked in the directory.
+pub fn path_link(old: &Dentry, new_dir: &Dentry) -> Result<()> {
+    call_hooks(|module, thread| module.path_link(thread, old, new_dir))
+}
+
+/// Checks whether the file of the name can be unlinked from the directory.
+pub fn path_unlink(dir: &Dentry, name: &str) -> Result<()> {
+    call_hooks(|module, thread| module.path_unlink(thread, dir, name))
+}
+
+/// Checks whether the directory of the name can be removed from the directory.
+pub fn path_rmdir(dir: &Dentry, name: &str) -> Result<()> {
+    call_hooks(|module, thread| module.path_rmdir(thread, dir, name))
+}
+
+/// Checks whether the file of the old name can be renamed to the new name, which may replace
+/// an existing file.
+pub fn path_rename(
+    old_dir: &Dentry,
+    old_name: &str,
+    new_dir: &Dentry,
+    new_name: &str,
+) -> Result<()> {
+    call_hooks(|module, thread| module.path_rename(thread, old_dir, old_name, new_dir, new_name))
+}
+
+/// Checks whether the file can be truncated by its path (i.e., with `truncate`).
+pub fn path_truncate(dentry: &Dentry) -> Result<()> {
+    call_hooks(|module, thread| module.path_truncate(thread, dentry))
+}
+
+/// Checks whether a socket of the protocol can be bound to the local port.
+pub fn socket_bind(protocol: Protocol, port: u16) -> Result<()> {
+    call_hooks(|module, thread| module.socket_bind(thread, protocol, port))
+}
+
+/// Checks whether a socket of the protocol can be connected to the remote port.
+pub fn socket_connect(protocol: Protocol, port: u16) -> Result<()> {
+    call_hooks(|module, thread| module.socket_connect(thread, protocol, port))
+}
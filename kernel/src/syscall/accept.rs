@@ -3,7 +3,7 @@
 use super::SyscallReturn;
 use crate::{
     fs::{
-        file_table::{get_file_fast, FdFlags, FileDesc},
+        file_table::{get_file_fast, max_fds, FdFlags, FileDesc},
         utils::{CreationFlags, StatusFlags},
     },
     prelude::*,
@@ -73,9 +73,10 @@ fn do_accept(
         write_socket_addr_to_user(&socket_addr, sockaddr_ptr, addrlen_ptr)?;
     }
 
+    let max_fds = max_fds(&ctx.process);
     let fd = {
         let mut file_table_locked = file_table.write();
-        file_table_locked.insert(connected_socket, fd_flags)
+        file_table_locked.insert(connected_socket, fd_flags, max_fds)?
     };
 
     Ok(fd)
@@ -0,0 +1,47 @@
+// SPDX-License-Identifier: MPL-2.0
+
+use super::{ClockId, SyscallReturn};
+use crate::{
+    prelude::*,
+    process::credentials::capabilities::CapSet,
+    time::{clockid_t, timekeeping, timex_t},
+};
+
+pub fn sys_adjtimex(timex_addr: Vaddr, ctx: &Context) -> Result<SyscallReturn> {
+    do_adjtimex(timex_addr, ctx)
+}
+
+pub fn sys_clock_adjtime(
+    clockid: clockid_t,
+    timex_addr: Vaddr,
+    ctx: &Context,
+) -> Result<SyscallReturn> {
+    debug!("clockid = {}", clockid);
+
+    // TODO: Support adjusting the dynamic clocks of the PTP devices.
+    let clock_id = ClockId::try_from(clockid)?;
+    if clock_id != ClockId::CLOCK_REALTIME {
+        return_errno_with_message!(Errno::EOPNOTSUPP, "the clock cannot be adjusted");
+    }
+
+    do_adjtimex(timex_addr, ctx)
+}
+
+fn do_adjtimex(timex_addr: Vaddr, ctx: &Context) -> Result<SyscallReturn> {
+    let user_space = ctx.user_space();
+    let mut timex = user_space.read_val::<timex_t>(timex_addr)?;
+    debug!(
+        "modes = {:#x}, offset = {}, freq = {}, status = {:#x}",
+        timex.modes, timex.offset, timex.freq, timex.status
+    );
+
+    let can_set_time = ctx
+        .posix_thread
+        .credentials()
+        .effective_capset()
+        .contains(CapSet::SYS_TIME);
+    let state = timekeeping::adjtimex(&mut timex, can_set_time)?;
+    user_space.write_val(timex_addr, &timex)?;
+
+    Ok(SyscallReturn::Return(state as _))
+}
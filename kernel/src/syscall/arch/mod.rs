@@ -2,12 +2,13 @@
 
 //! Implement the `syscall_dispatch` function and the const values of system call number such as `SYS_READ`.
 
-#[cfg(target_arch = "riscv64")]
+// AArch64 uses the same generic system call table as RISC-V.
+#[cfg(any(target_arch = "riscv64", target_arch = "aarch64"))]
 pub mod riscv;
 #[cfg(target_arch = "x86_64")]
 pub mod x86;
 
-#[cfg(target_arch = "riscv64")]
+#[cfg(any(target_arch = "riscv64", target_arch = "aarch64"))]
 pub use self::riscv::*;
 #[cfg(target_arch = "x86_64")]
 pub use self::x86::*;
@@ -3,6 +3,7 @@
 use crate::syscall::{
     accept::{sys_accept, sys_accept4},
     access::sys_faccessat,
+    adjtimex::{sys_adjtimex, sys_clock_adjtime},
     bind::sys_bind,
     brk::sys_brk,
     capget::sys_capget,
@@ -12,9 +13,11 @@ use crate::syscall::{
     chown::{sys_fchown, sys_fchownat},
     chroot::sys_chroot,
     clock_gettime::sys_clock_gettime,
+    clock_settime::sys_clock_settime,
     clone::{sys_clone, sys_clone3},
     close::sys_close,
     connect::sys_connect,
+    copy_file_range::sys_copy_file_range,
     dup::{sys_dup, sys_dup3},
     epoll::{sys_epoll_create1, sys_epoll_ctl, sys_epoll_pwait},
     eventfd::sys_eventfd2,
@@ -26,6 +29,7 @@ use crate::syscall::{
     flock::sys_flock,
     fsync::{sys_fdatasync, sys_fsync},
     futex::sys_futex,
+    getcpu::sys_getcpu,
     getcwd::sys_getcwd,
     getdents64::sys_getdents64,
     getegid::sys_getegid,
@@ -47,40 +51,66 @@ use crate::syscall::{
     gettimeofday::sys_gettimeofday,
     getuid::sys_getuid,
     impl_syscall_nums_and_dispatch_fn,
+    io_uring::{sys_io_uring_enter, sys_io_uring_setup},
     ioctl::sys_ioctl,
+    keyctl::{sys_add_key, sys_keyctl, sys_request_key},
     kill::sys_kill,
+    landlock::{sys_landlock_add_rule, sys_landlock_create_ruleset, sys_landlock_restrict_self},
     link::sys_linkat,
     listen::sys_listen,
     lseek::sys_lseek,
     madvise::sys_madvise,
+    mempolicy::{sys_get_mempolicy, sys_mbind, sys_set_mempolicy},
     mkdir::sys_mkdirat,
     mknod::sys_mknodat,
+    mlock::{sys_mlock, sys_mlock2, sys_mlockall, sys_munlock, sys_munlockall},
     mmap::sys_mmap,
     mount::sys_mount,
     mprotect::sys_mprotect,
+    mqueue::{
+        sys_mq_getsetattr, sys_mq_notify, sys_mq_open, sys_mq_timedreceive, sys_mq_timedsend,
+        sys_mq_unlink,
+    },
+    mremap::sys_mremap,
+    msgctl::sys_msgctl,
+    msgget::sys_msgget,
+    msgrcv::sys_msgrcv,
+    msgsnd::sys_msgsnd,
     msync::sys_msync,
     munmap::sys_munmap,
     nanosleep::{sys_clock_nanosleep, sys_nanosleep},
     open::sys_openat,
+    personality::sys_personality,
+    pidfd_open::sys_pidfd_open,
+    pidfd_send_signal::sys_pidfd_send_signal,
     pipe::sys_pipe2,
     prctl::sys_prctl,
     pread64::sys_pread64,
     preadv::{sys_preadv, sys_preadv2, sys_readv},
     prlimit64::{sys_getrlimit, sys_prlimit64, sys_setrlimit},
     pselect6::sys_pselect6,
+    ptrace::sys_ptrace,
     pwrite64::sys_pwrite64,
     pwritev::{sys_pwritev, sys_pwritev2, sys_writev},
     read::sys_read,
     readlink::sys_readlinkat,
+    reboot::sys_reboot,
     recvfrom::sys_recvfrom,
     recvmsg::sys_recvmsg,
     rename::sys_renameat,
     rt_sigaction::sys_rt_sigaction,
     rt_sigpending::sys_rt_sigpending,
     rt_sigprocmask::sys_rt_sigprocmask,
+    rt_sigqueueinfo::{sys_rt_sigqueueinfo, sys_rt_tgsigqueueinfo},
     rt_sigsuspend::sys_rt_sigsuspend,
     sched_affinity::{sys_sched_getaffinity, sys_sched_setaffinity},
+    sched_param::{
+        sys_sched_get_priority_max, sys_sched_get_priority_min, sys_sched_getparam,
+        sys_sched_getscheduler, sys_sched_rr_get_interval, sys_sched_setparam,
+        sys_sched_setscheduler,
+    },
     sched_yield::sys_sched_yield,
+    seccomp::sys_seccomp,
     semctl::sys_semctl,
     semget::sys_semget,
     semop::{sys_semop, sys_semtimedop},
@@ -88,13 +118,15 @@ use crate::syscall::{
     sendmsg::sys_sendmsg,
     sendto::sys_sendto,
     set_get_priority::{sys_get_priority, sys_set_priority},
-    set_robust_list::sys_set_robust_list,
+    set_get_robust_list::{sys_get_robust_list, sys_set_robust_list},
     set_tid_address::sys_set_tid_address,
     setfsgid::sys_setfsgid,
     setfsuid::sys_setfsuid,
     setgid::sys_setgid,
     setgroups::sys_setgroups,
+    sethostname::{sys_setdomainname, sys_sethostname},
     setitimer::{sys_getitimer, sys_setitimer},
+    setns::sys_setns,
     setpgid::sys_setpgid,
     setregid::sys_setregid,
     setresgid::sys_setresgid,
@@ -102,24 +134,37 @@ use crate::syscall::{
     setreuid::sys_setreuid,
     setsid::sys_setsid,
     setsockopt::sys_setsockopt,
+    settimeofday::sys_settimeofday,
     setuid::sys_setuid,
+    shmat::sys_shmat,
+    shmctl::sys_shmctl,
+    shmdt::sys_shmdt,
+    shmget::sys_shmget,
     shutdown::sys_shutdown,
     sigaltstack::sys_sigaltstack,
+    signalfd::sys_signalfd4,
     socket::sys_socket,
     socketpair::sys_socketpair,
+    splice::sys_splice,
     stat::{sys_fstat, sys_fstatat},
     statfs::{sys_fstatfs, sys_statfs},
+    swapon::{sys_swapoff, sys_swapon},
     symlink::sys_symlinkat,
     sync::sys_sync,
-    tgkill::sys_tgkill,
+    syslog::sys_syslog,
+    tgkill::{sys_tgkill, sys_tkill},
     timer_create::{sys_timer_create, sys_timer_delete},
-    timer_settime::{sys_timer_gettime, sys_timer_settime},
+    timer_settime::{sys_timer_getoverrun, sys_timer_gettime, sys_timer_settime},
+    timerfd::{sys_timerfd_create, sys_timerfd_gettime, sys_timerfd_settime},
     truncate::{sys_ftruncate, sys_truncate},
     umask::sys_umask,
     umount::sys_umount,
     uname::sys_uname,
     unlink::sys_unlinkat,
+    unshare::sys_unshare,
+    userfaultfd::sys_userfaultfd,
     utimens::sys_utimensat,
+    vmsplice::sys_vmsplice,
     wait4::sys_wait4,
     waitid::sys_waitid,
     write::sys_write,
@@ -172,37 +217,62 @@ impl_syscall_nums_and_dispatch_fn! {
     SYS_PWRITEV = 70             => sys_pwritev(args[..4]);
     SYS_SENDFILE64 = 71          => sys_sendfile(args[..4]);
     SYS_PSELECT6 = 72            => sys_pselect6(args[..6]);
+    SYS_SIGNALFD4 = 74           => sys_signalfd4(args[..4]);
+    SYS_VMSPLICE = 75            => sys_vmsplice(args[..4]);
+    SYS_SPLICE = 76              => sys_splice(args[..6]);
     SYS_READLINKAT = 78          => sys_readlinkat(args[..4]);
     SYS_NEWFSTATAT = 79          => sys_fstatat(args[..4]);
     SYS_NEWFSTAT = 80            => sys_fstat(args[..2]);
     SYS_SYNC = 81                => sys_sync(args[..0]);
     SYS_FSYNC = 82               => sys_fsync(args[..1]);
     SYS_FDATASYNC = 83           => sys_fdatasync(args[..1]);
+    SYS_TIMERFD_CREATE = 85      => sys_timerfd_create(args[..2]);
+    SYS_TIMERFD_SETTIME = 86     => sys_timerfd_settime(args[..4]);
+    SYS_TIMERFD_GETTIME = 87     => sys_timerfd_gettime(args[..2]);
     SYS_CAPGET = 90              => sys_capget(args[..2]);
     SYS_CAPSET = 91              => sys_capset(args[..2]);
+    SYS_PERSONALITY = 92         => sys_personality(args[..1]);
     SYS_EXIT = 93                => sys_exit(args[..1]);
     SYS_EXIT_GROUP = 94          => sys_exit_group(args[..1]);
     SYS_WAITID = 95              => sys_waitid(args[..5]);
     SYS_SET_TID_ADDRESS = 96     => sys_set_tid_address(args[..1]);
+    SYS_UNSHARE = 97             => sys_unshare(args[..1]);
     SYS_FUTEX = 98               => sys_futex(args[..6]);
     SYS_SET_ROBUST_LIST = 99     => sys_set_robust_list(args[..2]);
+    SYS_GET_ROBUST_LIST = 100    => sys_get_robust_list(args[..3]);
     SYS_NANOSLEEP = 101          => sys_nanosleep(args[..2]);
     SYS_GETITIMER = 102          => sys_getitimer(args[..2]);
     SYS_SETITIMER = 103          => sys_setitimer(args[..3]);
     SYS_TIMER_CREATE = 107       => sys_timer_create(args[..3]);
+    SYS_TIMER_GETTIME = 108      => sys_timer_gettime(args[..2]);
+    SYS_TIMER_GETOVERRUN = 109   => sys_timer_getoverrun(args[..1]);
+    SYS_TIMER_SETTIME = 110      => sys_timer_settime(args[..4]);
     SYS_TIMER_DELETE = 111       => sys_timer_delete(args[..1]);
+    SYS_CLOCK_SETTIME = 112      => sys_clock_settime(args[..2]);
+    SYS_SYSLOG = 116             => sys_syslog(args[..3]);
+    SYS_PTRACE = 117             => sys_ptrace(args[..4]);
+    SYS_SCHED_SETPARAM = 118     => sys_sched_setparam(args[..2]);
+    SYS_SCHED_SETSCHEDULER = 119 => sys_sched_setscheduler(args[..3]);
+    SYS_SCHED_GETSCHEDULER = 120 => sys_sched_getscheduler(args[..1]);
+    SYS_SCHED_GETPARAM = 121     => sys_sched_getparam(args[..2]);
     SYS_SCHED_SETAFFINITY = 122  => sys_sched_setaffinity(args[..3]);
     SYS_SCHED_GETAFFINITY = 123  => sys_sched_getaffinity(args[..3]);
     SYS_SCHED_YIELD = 124        => sys_sched_yield(args[..0]);
+    SYS_SCHED_GET_PRIORITY_MAX = 125 => sys_sched_get_priority_max(args[..1]);
+    SYS_SCHED_GET_PRIORITY_MIN = 126 => sys_sched_get_priority_min(args[..1]);
+    SYS_SCHED_RR_GET_INTERVAL = 127 => sys_sched_rr_get_interval(args[..2]);
     SYS_KILL = 129               => sys_kill(args[..2]);
+    SYS_TKILL = 130              => sys_tkill(args[..2]);
     SYS_TGKILL = 131             => sys_tgkill(args[..3]);
-    SYS_SIGALTSTACK = 132        => sys_sigaltstack(args[..2]);
+    SYS_SIGALTSTACK = 132        => sys_sigaltstack(args[..2], &user_ctx);
     SYS_RT_SIGSUSPEND = 133      => sys_rt_sigsuspend(args[..2]);
     SYS_RT_SIGACTION = 134       => sys_rt_sigaction(args[..4]);
     SYS_RT_SIGPROCMASK = 135     => sys_rt_sigprocmask(args[..4]);
     SYS_RT_SIGPENDING = 136      => sys_rt_sigpending(args[..2]);
+    SYS_RT_SIGQUEUEINFO = 138    => sys_rt_sigqueueinfo(args[..3]);
     SYS_SET_PRIORITY = 140       => sys_set_priority(args[..3]);
     SYS_GET_PRIORITY = 141       => sys_get_priority(args[..2]);
+    SYS_REBOOT = 142             => sys_reboot(args[..4]);
     SYS_SETREGID = 143           => sys_setregid(args[..2]);
     SYS_SETGID = 144             => sys_setgid(args[..1]);
     SYS_SETREUID = 145           => sys_setreuid(args[..2]);
@@ -220,12 +290,17 @@ impl_syscall_nums_and_dispatch_fn! {
     SYS_GETGROUPS = 158          => sys_getgroups(args[..2]);
     SYS_SETGROUPS = 159          => sys_setgroups(args[..2]);
     SYS_NEWUNAME = 160           => sys_uname(args[..1]);
+    SYS_SETHOSTNAME = 161        => sys_sethostname(args[..2]);
+    SYS_SETDOMAINNAME = 162      => sys_setdomainname(args[..2]);
     SYS_GETRLIMIT = 163          => sys_getrlimit(args[..2]);
     SYS_SETRLIMIT = 164          => sys_setrlimit(args[..2]);
     SYS_GETRUSAGE = 165          => sys_getrusage(args[..2]);
     SYS_UMASK = 166              => sys_umask(args[..1]);
     SYS_PRCTL = 167              => sys_prctl(args[..5]);
+    SYS_GETCPU = 168             => sys_getcpu(args[..3]);
     SYS_GETTIMEOFDAY = 169       => sys_gettimeofday(args[..1]);
+    SYS_SETTIMEOFDAY = 170       => sys_settimeofday(args[..1]);
+    SYS_ADJTIMEX = 171           => sys_adjtimex(args[..1]);
     SYS_GETPID = 172             => sys_getpid(args[..0]);
     SYS_GETPPID = 173            => sys_getppid(args[..0]);
     SYS_GETUID = 174             => sys_getuid(args[..0]);
@@ -233,9 +308,23 @@ impl_syscall_nums_and_dispatch_fn! {
     SYS_GETGID = 176             => sys_getgid(args[..0]);
     SYS_GETEGID = 177            => sys_getegid(args[..0]);
     SYS_GETTID = 178             => sys_gettid(args[..0]);
+    SYS_MQ_OPEN = 180            => sys_mq_open(args[..4]);
+    SYS_MQ_UNLINK = 181          => sys_mq_unlink(args[..1]);
+    SYS_MQ_TIMEDSEND = 182       => sys_mq_timedsend(args[..5]);
+    SYS_MQ_TIMEDRECEIVE = 183    => sys_mq_timedreceive(args[..5]);
+    SYS_MQ_NOTIFY = 184          => sys_mq_notify(args[..2]);
+    SYS_MQ_GETSETATTR = 185      => sys_mq_getsetattr(args[..3]);
+    SYS_MSGGET = 186             => sys_msgget(args[..2]);
+    SYS_MSGCTL = 187             => sys_msgctl(args[..3]);
+    SYS_MSGRCV = 188             => sys_msgrcv(args[..5]);
+    SYS_MSGSND = 189             => sys_msgsnd(args[..4]);
     SYS_SEMGET = 190             => sys_semget(args[..3]);
     SYS_SEMCTL = 191             => sys_semctl(args[..4]);
     SYS_SEMOP = 193              => sys_semop(args[..3]);
+    SYS_SHMGET = 194             => sys_shmget(args[..3]);
+    SYS_SHMCTL = 195             => sys_shmctl(args[..3]);
+    SYS_SHMAT = 196              => sys_shmat(args[..3]);
+    SYS_SHMDT = 197              => sys_shmdt(args[..1]);
     SYS_SOCKET = 198             => sys_socket(args[..3]);
     SYS_SOCKETPAIR = 199         => sys_socketpair(args[..4]);
     SYS_BIND = 200               => sys_bind(args[..3]);
@@ -253,25 +342,50 @@ impl_syscall_nums_and_dispatch_fn! {
     SYS_RECVMSG = 212            => sys_recvmsg(args[..3]);
     SYS_BRK = 214                => sys_brk(args[..1]);
     SYS_MUNMAP = 215             => sys_munmap(args[..2]);
+    SYS_MREMAP = 216             => sys_mremap(args[..5]);
+    SYS_ADD_KEY = 217            => sys_add_key(args[..5]);
+    SYS_REQUEST_KEY = 218        => sys_request_key(args[..4]);
+    SYS_KEYCTL = 219             => sys_keyctl(args[..5]);
     SYS_CLONE = 220              => sys_clone(args[..5], &user_ctx);
     SYS_EXECVE = 221             => sys_execve(args[..3], &mut user_ctx);
     SYS_MMAP = 222               => sys_mmap(args[..6]);
+    SYS_SWAPON = 224             => sys_swapon(args[..2]);
+    SYS_SWAPOFF = 225            => sys_swapoff(args[..1]);
     SYS_MPROTECT = 226           => sys_mprotect(args[..3]);
     SYS_MSYNC = 227              => sys_msync(args[..3]);
+    SYS_MLOCK = 228              => sys_mlock(args[..2]);
+    SYS_MUNLOCK = 229            => sys_munlock(args[..2]);
+    SYS_MLOCKALL = 230           => sys_mlockall(args[..1]);
+    SYS_MUNLOCKALL = 231         => sys_munlockall(args[..0]);
     SYS_MADVISE = 233            => sys_madvise(args[..3]);
+    SYS_MBIND = 235              => sys_mbind(args[..6]);
+    SYS_GET_MEMPOLICY = 236      => sys_get_mempolicy(args[..5]);
+    SYS_SET_MEMPOLICY = 237      => sys_set_mempolicy(args[..3]);
+    SYS_RT_TGSIGQUEUEINFO = 240  => sys_rt_tgsigqueueinfo(args[..4]);
     SYS_ACCEPT4 = 242            => sys_accept4(args[..4]);
     SYS_WAIT4 = 260              => sys_wait4(args[..4]);
     SYS_PRLIMIT64 = 261          => sys_prlimit64(args[..4]);
+    SYS_CLOCK_ADJTIME = 266      => sys_clock_adjtime(args[..2]);
+    SYS_SETNS = 268              => sys_setns(args[..2]);
+    SYS_SECCOMP = 277            => sys_seccomp(args[..3]);
     SYS_GETRANDOM = 278          => sys_getrandom(args[..3]);
     SYS_EXECVEAT = 281           => sys_execveat(args[..5], &mut user_ctx);
+    SYS_USERFAULTFD = 282        => sys_userfaultfd(args[..1]);
+    SYS_MLOCK2 = 284             => sys_mlock2(args[..3]);
+    SYS_COPY_FILE_RANGE = 285    => sys_copy_file_range(args[..6]);
     SYS_PREADV2 = 286            => sys_preadv2(args[..5]);
     SYS_PWRITEV2 = 287           => sys_pwritev2(args[..5]);
     SYS_PRLIMIT64 = 302          => sys_prlimit64(args[..4]);
     SYS_CLOCK_GETTIME = 403      => sys_clock_gettime(args[..2]);
     SYS_CLOCK_NANOSLEEP = 407    => sys_clock_nanosleep(args[..4]);
-    SYS_TIMER_GETTIME = 408      => sys_timer_gettime(args[..2]);
-    SYS_TIMER_SETTIME = 409      => sys_timer_settime(args[..4]);
     SYS_UTIMENSAT = 412          => sys_utimensat(args[..4]);
     SYS_SEMTIMEDOP = 420         => sys_semtimedop(args[..4]);
+    SYS_PIDFD_SEND_SIGNAL = 424  => sys_pidfd_send_signal(args[..4]);
+    SYS_IO_URING_SETUP = 425     => sys_io_uring_setup(args[..2]);
+    SYS_IO_URING_ENTER = 426     => sys_io_uring_enter(args[..6]);
+    SYS_PIDFD_OPEN = 434         => sys_pidfd_open(args[..2]);
     SYS_CLONE3 = 435             => sys_clone3(args[..2], &user_ctx);
+    SYS_LANDLOCK_CREATE_RULESET = 444 => sys_landlock_create_ruleset(args[..3]);
+    SYS_LANDLOCK_ADD_RULE = 445 => sys_landlock_add_rule(args[..4]);
+    SYS_LANDLOCK_RESTRICT_SELF = 446 => sys_landlock_restrict_self(args[..2]);
 }
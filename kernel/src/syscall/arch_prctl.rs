@@ -1,6 +1,6 @@
 // SPDX-License-Identifier: MPL-2.0
 
-use ostd::cpu::UserContext;
+use ostd::{cpu::UserContext, mm::MAX_USERSPACE_VADDR};
 
 use super::SyscallReturn;
 use crate::prelude::*;
@@ -26,8 +26,8 @@ pub fn sys_arch_prctl(
         "arch_prctl_code: {:?}, addr = 0x{:x}",
         arch_prctl_code, addr
     );
-    let res = do_arch_prctl(arch_prctl_code, addr, ctx, user_ctx).unwrap();
-    Ok(SyscallReturn::Return(res as _))
+    do_arch_prctl(arch_prctl_code, addr, ctx, user_ctx)?;
+    Ok(SyscallReturn::Return(0))
 }
 
 pub fn do_arch_prctl(
@@ -35,17 +35,28 @@ pub fn do_arch_prctl(
     addr: u64,
     ctx: &Context,
     user_ctx: &mut UserContext,
-) -> Result<u64> {
+) -> Result<()> {
     match code {
-        ArchPrctlCode::ARCH_SET_FS => {
-            ctx.task.set_tls_pointer(addr as usize);
-            user_ctx.set_tls_pointer(addr as usize);
-            user_ctx.activate_tls_pointer();
-            Ok(0)
+        ArchPrctlCode::ARCH_SET_FS | ArchPrctlCode::ARCH_SET_GS => {
+            // Like Linux, the bases cannot point to the kernel space.
+            if addr >= MAX_USERSPACE_VADDR as u64 {
+                return_errno_with_message!(Errno::EPERM, "the base is not a user-space address");
+            }
+            // The new bases are loaded on the CPU when returning to the user space.
+            if matches!(code, ArchPrctlCode::ARCH_SET_FS) {
+                user_ctx.set_tls_pointer(addr as usize);
+            } else {
+                user_ctx.set_gsbase(addr as usize);
+            }
         }
-        ArchPrctlCode::ARCH_GET_FS => Ok(user_ctx.tls_pointer() as u64),
-        ArchPrctlCode::ARCH_GET_GS | ArchPrctlCode::ARCH_SET_GS => {
-            return_errno_with_message!(Errno::EINVAL, "GS cannot be accessed from the user space")
+        ArchPrctlCode::ARCH_GET_FS => {
+            let fs_base = user_ctx.fsbase() as u64;
+            ctx.user_space().write_val(addr as Vaddr, &fs_base)?;
+        }
+        ArchPrctlCode::ARCH_GET_GS => {
+            let gs_base = user_ctx.gsbase() as u64;
+            ctx.user_space().write_val(addr as Vaddr, &gs_base)?;
         }
     }
+    Ok(())
 }
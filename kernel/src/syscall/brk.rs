@@ -10,8 +10,8 @@ pub fn sys_brk(heap_end: u64, ctx: &Context) -> Result<SyscallReturn> {
         Some(heap_end as usize)
     };
     debug!("new heap end = {:x?}", heap_end);
-    let user_heap = ctx.process.heap();
-    let new_heap_end = user_heap.brk(new_heap_end)?;
+    let process_vm = ctx.thread_local.process_vm();
+    let new_heap_end = process_vm.heap().brk(new_heap_end, ctx)?;
 
     Ok(SyscallReturn::Return(new_heap_end as _))
 }
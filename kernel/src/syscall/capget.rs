@@ -3,8 +3,12 @@
 use super::SyscallReturn;
 use crate::{
     prelude::*,
-    process::credentials::c_types::{
-        cap_user_data_t, cap_user_header_t, LINUX_CAPABILITY_VERSION_3,
+    process::{
+        credentials::c_types::{
+            cap_user_data_t, cap_user_header_t, LINUX_CAPABILITY_VERSION_1,
+            LINUX_CAPABILITY_VERSION_2, LINUX_CAPABILITY_VERSION_3,
+        },
+        posix_thread::{thread_table, AsPosixThread},
     },
 };
 
@@ -13,38 +17,75 @@ pub fn sys_capget(
     cap_user_data_addr: Vaddr,
     ctx: &Context,
 ) -> Result<SyscallReturn> {
-    let user_space = ctx.user_space();
-    let cap_user_header: cap_user_header_t =
-        user_space.read_val::<cap_user_header_t>(cap_user_header_addr)?;
-
-    if cap_user_header.version != LINUX_CAPABILITY_VERSION_3 {
-        return_errno_with_message!(Errno::EINVAL, "not supported (capability version is not 3)");
+    // A null data pointer is used to probe the preferred version, which is written to the
+    // header if the version is invalid.
+    let (cap_user_header, nr_data) = match read_cap_user_header(cap_user_header_addr, ctx) {
+        Err(err) if err.error() == Errno::EINVAL && cap_user_data_addr == 0 => {
+            return Ok(SyscallReturn::Return(0));
+        }
+        result => result?,
     };
-
-    // Extract target pid and validate whether it represents the current process.
-    let header_pid = cap_user_header.pid;
-    // Capget only query current process's credential. Namely, it only allows header->pid == 0
-    // or header->pid == getpid(), which are equivalent.
-    // See https://linux.die.net/man/2/capget (Section. With VFS capability support) for details.
-    if header_pid != 0 && header_pid != ctx.process.pid() {
-        return_errno_with_message!(Errno::EINVAL, "invalid pid");
+    if cap_user_data_addr == 0 {
+        return Ok(SyscallReturn::Return(0));
     }
 
-    let credentials = ctx.posix_thread.credentials();
-    let inheritable_capset = credentials.inheritable_capset();
-    let permitted_capset = credentials.permitted_capset();
-    let effective_capset = credentials.effective_capset();
+    // Like Linux, the capabilities of any thread can be queried.
+    let header_pid = cap_user_header.pid;
+    let credentials = if header_pid == 0 || header_pid == ctx.posix_thread.ns_tid() {
+        ctx.posix_thread.credentials()
+    } else {
+        let thread = ctx
+            .process
+            .pid_ns()
+            .global_id(header_pid)
+            .and_then(thread_table::get_thread)
+            .ok_or_else(|| Error::with_message(Errno::ESRCH, "the thread does not exist"))?;
+        thread.as_posix_thread().unwrap().credentials()
+    };
 
     // Annoying legacy format with 64-bit capabilities exposed as two sets of 32-bit fields,
     // so we need to split the capability values up.
-    let result = cap_user_data_t {
-        // Note we silently drop the upper capabilities here.
-        // This behavior is considered fail-safe behavior.
-        effective: effective_capset.as_u32(),
-        permitted: permitted_capset.as_u32(),
-        inheritable: inheritable_capset.as_u32(),
-    };
+    let effective = credentials.effective_capset().bits();
+    let permitted = credentials.permitted_capset().bits();
+    let inheritable = credentials.inheritable_capset().bits();
+    let user_space = ctx.user_space();
+    for i in 0..nr_data {
+        let data = cap_user_data_t {
+            effective: (effective >> (32 * i)) as u32,
+            permitted: (permitted >> (32 * i)) as u32,
+            inheritable: (inheritable >> (32 * i)) as u32,
+        };
+        user_space.write_val(
+            cap_user_data_addr + i * core::mem::size_of::<cap_user_data_t>(),
+            &data,
+        )?;
+    }
 
-    user_space.write_val(cap_user_data_addr, &result)?;
     Ok(SyscallReturn::Return(0))
 }
+
+/// Reads the header of `capget` and `capset`.
+///
+/// Returns the header and the number of [`cap_user_data_t`] that follow the header, which is
+/// determined by the version. If the version is unknown, the preferred version is written back
+/// to the header, so that the user can probe it.
+pub(super) fn read_cap_user_header(
+    cap_user_header_addr: Vaddr,
+    ctx: &Context,
+) -> Result<(cap_user_header_t, usize)> {
+    let user_space = ctx.user_space();
+    let mut cap_user_header: cap_user_header_t = user_space.read_val(cap_user_header_addr)?;
+
+    let nr_data = match cap_user_header.version {
+        // Version 1 only supports 32-bit capabilities.
+        LINUX_CAPABILITY_VERSION_1 => 1,
+        LINUX_CAPABILITY_VERSION_2 | LINUX_CAPABILITY_VERSION_3 => 2,
+        _ => {
+            cap_user_header.version = LINUX_CAPABILITY_VERSION_3;
+            user_space.write_val(cap_user_header_addr, &cap_user_header)?;
+            return_errno_with_message!(Errno::EINVAL, "the capability version is invalid");
+        }
+    };
+
+    Ok((cap_user_header, nr_data))
+}
@@ -1,12 +1,9 @@
 // SPDX-License-Identifier: MPL-2.0
 
-use super::SyscallReturn;
+use super::{capget::read_cap_user_header, SyscallReturn};
 use crate::{
     prelude::*,
-    process::credentials::{
-        c_types::{cap_user_data_t, cap_user_header_t, LINUX_CAPABILITY_VERSION_3},
-        capabilities::CapSet,
-    },
+    process::credentials::{c_types::cap_user_data_t, capabilities::CapSet},
 };
 
 fn make_kernel_cap(low: u32, high: u32) -> u64 {
@@ -18,43 +15,71 @@ pub fn sys_capset(
     cap_user_data_addr: Vaddr,
     ctx: &Context,
 ) -> Result<SyscallReturn> {
-    let user_space = ctx.user_space();
-    let cap_user_header: cap_user_header_t =
-        user_space.read_val::<cap_user_header_t>(cap_user_header_addr)?;
-
-    if cap_user_header.version != LINUX_CAPABILITY_VERSION_3 {
-        return_errno_with_message!(Errno::EINVAL, "not supported (capability version is not 3)");
-    }
+    let (cap_user_header, nr_data) = read_cap_user_header(cap_user_header_addr, ctx)?;
 
     // The ability to set capabilities of any other process has been deprecated.
     // See: https://elixir.bootlin.com/linux/v6.9.3/source/kernel/capability.c#L209 for more details.
     let header_pid = cap_user_header.pid;
-    if header_pid != 0 && header_pid != ctx.process.pid() {
-        return_errno_with_message!(Errno::EINVAL, "invalid pid");
+    if header_pid != 0 && header_pid != ctx.posix_thread.ns_tid() {
+        return_errno_with_message!(
+            Errno::EPERM,
+            "the capabilities of other threads cannot be set"
+        );
+    }
+
+    // Convert the two sets of 32-bit fields to 64-bit capabilities.
+    let user_space = ctx.user_space();
+    let mut cap_user_data = [cap_user_data_t::new_zeroed(); 2];
+    for (i, data) in cap_user_data.iter_mut().take(nr_data).enumerate() {
+        *data = user_space
+            .read_val(cap_user_data_addr + i * core::mem::size_of::<cap_user_data_t>())?;
     }
+    let [low, high] = cap_user_data;
+    let inheritable =
+        CapSet::from_bits_truncate(make_kernel_cap(low.inheritable, high.inheritable));
+    let permitted = CapSet::from_bits_truncate(make_kernel_cap(low.permitted, high.permitted));
+    let effective = CapSet::from_bits_truncate(make_kernel_cap(low.effective, high.effective));
 
-    // Check if the current process has CAP_SET_CAP capability
-    if !(ctx
-        .posix_thread
-        .credentials()
-        .permitted_capset()
-        .contains(CapSet::SETPCAP))
+    let credentials = ctx.posix_thread.credentials();
+    let old_inheritable = credentials.inheritable_capset();
+    let old_permitted = credentials.permitted_capset();
+
+    // Like Linux, the new capabilities are limited as follows:
+    // - Without `CAP_SETPCAP`, the inheritable capabilities can only be chosen from the
+    //   permitted ones.
+    // - The inheritable capabilities cannot exceed the bounding set.
+    // - The permitted capabilities can only be dropped.
+    // - The effective capabilities must be permitted.
+    // Reference: <https://man7.org/linux/man-pages/man2/capset.2.html>.
+    if !credentials.effective_capset().contains(CapSet::SETPCAP)
+        && !(old_inheritable | old_permitted).contains(inheritable)
     {
-        return_errno_with_message!(Errno::EPERM, "permission denied");
+        return_errno_with_message!(
+            Errno::EPERM,
+            "adding inheritable capabilities that are not permitted requires CAP_SETPCAP"
+        );
+    }
+    if !(old_inheritable | credentials.bounding_capset()).contains(inheritable) {
+        return_errno_with_message!(
+            Errno::EPERM,
+            "the inheritable capabilities exceed the bounding set"
+        );
+    }
+    if !old_permitted.contains(permitted) {
+        return_errno_with_message!(Errno::EPERM, "the permitted capabilities cannot be added");
+    }
+    if !permitted.contains(effective) {
+        return_errno_with_message!(Errno::EPERM, "the effective capabilities must be permitted");
     }
 
-    // Convert the cap(u32) to u64
-    let cap_user_data: cap_user_data_t =
-        user_space.read_val::<cap_user_data_t>(cap_user_data_addr)?;
-    let inheritable = make_kernel_cap(cap_user_data.inheritable, 0);
-    let permitted = make_kernel_cap(cap_user_data.permitted, 0);
-    let effective = make_kernel_cap(cap_user_data.effective, 0);
+    // The ambient capabilities must be both permitted and inheritable.
+    let ambient = credentials.ambient_capset() & permitted & inheritable;
 
     let credentials = ctx.posix_thread.credentials_mut();
-
-    credentials.set_inheritable_capset(CapSet::from_bits_truncate(inheritable));
-    credentials.set_permitted_capset(CapSet::from_bits_truncate(permitted));
-    credentials.set_effective_capset(CapSet::from_bits_truncate(effective));
+    credentials.set_inheritable_capset(inheritable);
+    credentials.set_permitted_capset(permitted);
+    credentials.set_effective_capset(effective);
+    credentials.set_ambient_capset(ambient);
 
     Ok(SyscallReturn::Return(0))
 }
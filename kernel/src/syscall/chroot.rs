@@ -4,6 +4,7 @@ use super::SyscallReturn;
 use crate::{
     fs::{fs_resolver::FsPath, utils::InodeType},
     prelude::*,
+    process::credentials::capabilities::CapSet,
     syscall::constants::MAX_FILENAME_LEN,
 };
 
@@ -11,7 +12,17 @@ pub fn sys_chroot(path_ptr: Vaddr, ctx: &Context) -> Result<SyscallReturn> {
     let path = ctx.user_space().read_cstring(path_ptr, MAX_FILENAME_LEN)?;
     debug!("path = {:?}", path);
 
-    let mut fs = ctx.posix_thread.fs().resolver().write();
+    if !ctx
+        .posix_thread
+        .credentials()
+        .effective_capset()
+        .contains(CapSet::SYS_CHROOT)
+    {
+        return_errno_with_message!(Errno::EPERM, "changing the root requires CAP_SYS_CHROOT");
+    }
+
+    let fs_ref = ctx.thread_local.borrow_fs();
+    let mut fs = fs_ref.resolver().write();
     let dentry = {
         let path = path.to_string_lossy();
         if path.is_empty() {
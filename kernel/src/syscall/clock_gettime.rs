@@ -2,20 +2,23 @@
 
 use core::time::Duration;
 
+use cfg_if::cfg_if;
 use int_to_c_enum::TryFromInt;
 
 use super::SyscallReturn;
 use crate::{
+    fs::file_table::{get_file_fast, FileDesc},
     prelude::*,
     process::{
         posix_thread::{thread_table, AsPosixThread},
-        process_table,
+        process_table, Process,
     },
+    thread::Thread,
     time::{
         clockid_t,
         clocks::{
             BootTimeClock, MonotonicClock, MonotonicCoarseClock, MonotonicRawClock, RealTimeClock,
-            RealTimeCoarseClock,
+            RealTimeCoarseClock, TaiClock,
         },
         timespec_t, Clock,
     },
@@ -49,6 +52,9 @@ pub enum ClockId {
     CLOCK_REALTIME_COARSE = 5,
     CLOCK_MONOTONIC_COARSE = 6,
     CLOCK_BOOTTIME = 7,
+    CLOCK_REALTIME_ALARM = 8,
+    CLOCK_BOOTTIME_ALARM = 9,
+    CLOCK_TAI = 11,
 }
 
 /// The information decoded from a dynamic clock ID.
@@ -68,7 +74,6 @@ pub enum ClockId {
 pub enum DynamicClockIdInfo {
     Pid(u32, DynamicClockType),
     Tid(u32, DynamicClockType),
-    #[allow(dead_code)]
     Fd(u32),
 }
 
@@ -115,12 +120,18 @@ pub fn read_clock(clockid: clockid_t, ctx: &Context) -> Result<Duration> {
     if clockid >= 0 {
         let clock_id = ClockId::try_from(clockid)?;
         match clock_id {
-            ClockId::CLOCK_REALTIME => Ok(RealTimeClock::get().read_time()),
+            // The alarm clocks are the same as their counterparts when they are read.
+            ClockId::CLOCK_REALTIME | ClockId::CLOCK_REALTIME_ALARM => {
+                Ok(RealTimeClock::get().read_time())
+            }
             ClockId::CLOCK_MONOTONIC => Ok(MonotonicClock::get().read_time()),
             ClockId::CLOCK_MONOTONIC_RAW => Ok(MonotonicRawClock::get().read_time()),
             ClockId::CLOCK_REALTIME_COARSE => Ok(RealTimeCoarseClock::get().read_time()),
             ClockId::CLOCK_MONOTONIC_COARSE => Ok(MonotonicCoarseClock::get().read_time()),
-            ClockId::CLOCK_BOOTTIME => Ok(BootTimeClock::get().read_time()),
+            ClockId::CLOCK_BOOTTIME | ClockId::CLOCK_BOOTTIME_ALARM => {
+                Ok(BootTimeClock::get().read_time())
+            }
+            ClockId::CLOCK_TAI => Ok(TaiClock::get().read_time()),
             ClockId::CLOCK_PROCESS_CPUTIME_ID => Ok(ctx.process.prof_clock().read_time()),
             ClockId::CLOCK_THREAD_CPUTIME_ID => Ok(ctx.posix_thread.prof_clock().read_time()),
         }
@@ -128,28 +139,70 @@ pub fn read_clock(clockid: clockid_t, ctx: &Context) -> Result<Duration> {
         let dynamic_clockid_info = DynamicClockIdInfo::try_from(clockid)?;
         match dynamic_clockid_info {
             DynamicClockIdInfo::Pid(pid, clock_type) => {
-                let process = process_table::get_process(pid)
-                    .ok_or_else(|| crate::Error::with_message(Errno::EINVAL, "invalid clock ID"))?;
+                let process = cpu_clock_process(pid, ctx)?;
                 match clock_type {
-                    DynamicClockType::Profiling => Ok(process.prof_clock().read_time()),
+                    DynamicClockType::Profiling | DynamicClockType::Scheduling => {
+                        Ok(process.prof_clock().read_time())
+                    }
                     DynamicClockType::Virtual => Ok(process.prof_clock().user_clock().read_time()),
-                    // TODO: support scheduling clock and fd clock.
-                    _ => unimplemented!(),
+                    DynamicClockType::FD => unreachable!(),
                 }
             }
             DynamicClockIdInfo::Tid(tid, clock_type) => {
-                let thread = thread_table::get_thread(tid)
-                    .ok_or_else(|| Error::with_message(Errno::EINVAL, "invalid clock ID"))?;
+                let thread = cpu_clock_thread(tid, ctx)?;
                 let posix_thread = thread.as_posix_thread().unwrap();
                 match clock_type {
-                    DynamicClockType::Profiling => Ok(posix_thread.prof_clock().read_time()),
+                    DynamicClockType::Profiling | DynamicClockType::Scheduling => {
+                        Ok(posix_thread.prof_clock().read_time())
+                    }
                     DynamicClockType::Virtual => {
                         Ok(posix_thread.prof_clock().user_clock().read_time())
                     }
-                    _ => unimplemented!(),
+                    DynamicClockType::FD => unreachable!(),
                 }
             }
-            DynamicClockIdInfo::Fd(_) => unimplemented!(),
+            DynamicClockIdInfo::Fd(fd) => read_fd_clock(fd as FileDesc, ctx),
+        }
+    }
+}
+
+/// Finds the process of a process CPU-time clock, where PID 0 refers to the current process.
+pub(super) fn cpu_clock_process(pid: u32, ctx: &Context) -> Result<Arc<Process>> {
+    if pid == 0 {
+        return Ok(ctx.posix_thread.process());
+    }
+
+    ctx.process
+        .pid_ns()
+        .global_id(pid)
+        .and_then(process_table::get_process)
+        .ok_or_else(|| Error::with_message(Errno::EINVAL, "invalid clock ID"))
+}
+
+/// Finds the thread of a thread CPU-time clock, where TID 0 refers to the current thread.
+pub(super) fn cpu_clock_thread(tid: u32, ctx: &Context) -> Result<Arc<Thread>> {
+    if tid == 0 {
+        return Ok(current_thread!());
+    }
+
+    ctx.process
+        .pid_ns()
+        .global_id(tid)
+        .and_then(thread_table::get_thread)
+        .ok_or_else(|| Error::with_message(Errno::EINVAL, "invalid clock ID"))
+}
+
+/// Reads the time of the clock of the device file with the file descriptor.
+fn read_fd_clock(fd: FileDesc, ctx: &Context) -> Result<Duration> {
+    let mut file_table = ctx.thread_local.file_table().borrow_mut();
+    let file = get_file_fast!(&mut file_table, fd);
+
+    cfg_if! {
+        if #[cfg(target_arch = "x86_64")] {
+            crate::device::ptp::read_clock_of(&**file)
+        } else {
+            let _ = file;
+            return_errno_with_message!(Errno::EINVAL, "the file is not a clock");
         }
     }
 }
@@ -0,0 +1,47 @@
+// SPDX-License-Identifier: MPL-2.0
+
+use core::time::Duration;
+
+use super::{ClockId, SyscallReturn};
+use crate::{
+    prelude::*,
+    process::credentials::capabilities::CapSet,
+    time::{clockid_t, timekeeping, timespec_t},
+};
+
+pub fn sys_clock_settime(
+    clockid: clockid_t,
+    timespec_addr: Vaddr,
+    ctx: &Context,
+) -> Result<SyscallReturn> {
+    let timespec = ctx.user_space().read_val::<timespec_t>(timespec_addr)?;
+    debug!("clockid = {}, timespec = {:?}", clockid, timespec);
+
+    // Only the real-time clock can be set, which is the same as Linux.
+    let clock_id = ClockId::try_from(clockid)?;
+    if clock_id != ClockId::CLOCK_REALTIME {
+        return_errno_with_message!(Errno::EINVAL, "the clock cannot be set");
+    }
+    if timespec.nsec >= 1_000_000_000 {
+        return_errno_with_message!(Errno::EINVAL, "the nanoseconds are not normalized");
+    }
+    let time = Duration::try_from(timespec)?;
+
+    check_sys_time_capability(ctx)?;
+    timekeeping::set_real_time(time)?;
+
+    Ok(SyscallReturn::Return(0))
+}
+
+/// Checks whether the current thread is allowed to set the system clocks.
+pub(super) fn check_sys_time_capability(ctx: &Context) -> Result<()> {
+    if !ctx
+        .posix_thread
+        .credentials()
+        .effective_capset()
+        .contains(CapSet::SYS_TIME)
+    {
+        return_errno_with_message!(Errno::EPERM, "setting the clock requires CAP_SYS_TIME");
+    }
+    Ok(())
+}
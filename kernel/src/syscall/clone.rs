@@ -23,7 +23,7 @@ pub fn sys_clone(
 ) -> Result<SyscallReturn> {
     let args = CloneArgs::for_clone(clone_flags, parent_tidptr, child_tidptr, tls, new_sp)?;
     debug!("flags = {:?}, child_stack_ptr = 0x{:x}, parent_tid_ptr = 0x{:x?}, child tid ptr = 0x{:x}, tls = 0x{:x}", args.flags, args.stack, args.parent_tid, args.child_tid, args.tls);
-    let child_pid = clone_child(ctx, parent_context, args).unwrap();
+    let child_pid = clone_child(ctx, parent_context, args)?;
     Ok(SyscallReturn::Return(child_pid as _))
 }
 
@@ -45,7 +45,7 @@ pub fn sys_clone3(
     let clone_args = {
         let args: Clone3Args = ctx.user_space().read_val(clong_args_addr)?;
         trace!("clone3 args = {:x?}", args);
-        CloneArgs::from(args)
+        CloneArgs::try_from(args)?
     };
     debug!("clone args = {:x?}", clone_args);
 
@@ -81,13 +81,11 @@ struct Clone3Args {
     cgroup: u64,
 }
 
-impl From<Clone3Args> for CloneArgs {
-    fn from(value: Clone3Args) -> Self {
-        // TODO: deal with pidfd, set_tid, set_tid_size, cgroup
-        if value.pidfd != 0 {
-            warn!("pidfd is not supported");
-        }
+impl TryFrom<Clone3Args> for CloneArgs {
+    type Error = Error;
 
+    fn try_from(value: Clone3Args) -> Result<Self> {
+        // TODO: deal with set_tid, set_tid_size, cgroup
         if value.set_tid != 0 || value.set_tid_size != 0 {
             warn!("set_tid is not supported");
         }
@@ -96,18 +94,36 @@ impl From<Clone3Args> for CloneArgs {
             warn!("cgroup is not supported");
         }
 
-        Self {
-            flags: CloneFlags::from_bits_truncate(value.flags as u32),
-            _pidfd: Some(value.pidfd),
+        let flags = CloneFlags::from_bits_truncate(value.flags as u32);
+
+        let exit_signal = if value.exit_signal == 0 {
+            None
+        } else if value.exit_signal > u8::MAX as u64 {
+            return_errno_with_message!(Errno::EINVAL, "the exit signal is invalid");
+        } else {
+            Some(SigNum::try_from(value.exit_signal as u8)?)
+        };
+        if exit_signal.is_some()
+            && flags.intersects(CloneFlags::CLONE_THREAD | CloneFlags::CLONE_PARENT)
+        {
+            return_errno_with_message!(
+                Errno::EINVAL,
+                "the exit signal cannot be specified with `CLONE_THREAD` or `CLONE_PARENT`"
+            );
+        }
+
+        Ok(Self {
+            flags,
+            pidfd: Some(value.pidfd as _),
             child_tid: value.child_tid as _,
             parent_tid: Some(value.parent_tid as _),
-            exit_signal: (value.exit_signal != 0).then(|| SigNum::from_u8(value.exit_signal as u8)),
+            exit_signal,
             stack: value.stack,
             stack_size: NonZeroU64::new(value.stack_size),
             tls: value.tls,
             _set_tid: Some(value.set_tid),
             _set_tid_size: Some(value.set_tid_size),
             _cgroup: Some(value.cgroup),
-        }
+        })
     }
 }
@@ -0,0 +1,48 @@
+// SPDX-License-Identifier: MPL-2.0
+
+use crate::{
+    fs::{file_table::FileDesc, utils::IoctlCmd},
+    prelude::*,
+    syscall::{ioctl::sys_ioctl, SyscallReturn},
+};
+
+/// Performs the commands whose arguments have the same layouts for the 32-bit programs.
+///
+/// The other commands are rejected, since their arguments would be misinterpreted.
+pub(super) fn sys_compat_ioctl(
+    fd: FileDesc,
+    cmd: u32,
+    arg: Vaddr,
+    ctx: &Context,
+) -> Result<SyscallReturn> {
+    let ioctl_cmd = IoctlCmd::try_from(cmd)?;
+    match ioctl_cmd {
+        IoctlCmd::TCGETS
+        | IoctlCmd::TCSETS
+        | IoctlCmd::TCSETSW
+        | IoctlCmd::TCSETSF
+        | IoctlCmd::TIOCSCTTY
+        | IoctlCmd::TIOCGPGRP
+        | IoctlCmd::TIOCSPGRP
+        | IoctlCmd::FIONREAD
+        | IoctlCmd::TIOCGWINSZ
+        | IoctlCmd::TIOCSWINSZ
+        | IoctlCmd::FIONBIO
+        | IoctlCmd::TIOCNOTTY
+        | IoctlCmd::FIONCLEX
+        | IoctlCmd::FIOCLEX
+        | IoctlCmd::FIOASYNC
+        | IoctlCmd::TIOCGPTN
+        | IoctlCmd::TIOCSPTLCK
+        | IoctlCmd::TIOCGPTPEER
+        | IoctlCmd::RNDGETENTCNT
+        | IoctlCmd::RNDADDTOENTCNT
+        | IoctlCmd::RNDZAPENTCNT
+        | IoctlCmd::RNDCLEARPOOL
+        | IoctlCmd::RNDRESEEDCRNG => sys_ioctl(fd, cmd, arg, ctx),
+        _ => return_errno_with_message!(
+            Errno::ENOTTY,
+            "the ioctl command is not supported for 32-bit programs"
+        ),
+    }
+}
@@ -0,0 +1,40 @@
+// SPDX-License-Identifier: MPL-2.0
+
+use crate::{
+    fs::file_table::FileDesc,
+    prelude::*,
+    syscall::{lseek::sys_lseek, SyscallReturn},
+};
+
+pub(super) fn sys_compat_lseek(
+    fd: FileDesc,
+    offset: i32,
+    whence: u32,
+    ctx: &Context,
+) -> Result<SyscallReturn> {
+    let SyscallReturn::Return(new_offset) = sys_lseek(fd, offset as isize, whence, ctx)? else {
+        unreachable!("lseek always returns a value");
+    };
+    if new_offset > i32::MAX as isize {
+        return_errno_with_message!(Errno::EOVERFLOW, "the offset cannot be represented");
+    }
+    Ok(SyscallReturn::Return(new_offset))
+}
+
+/// Repositions the offset with a 64-bit offset, which is split into two 32-bit arguments.
+pub(super) fn sys_llseek(
+    fd: FileDesc,
+    offset_high: u32,
+    offset_low: u32,
+    result_addr: Vaddr,
+    whence: u32,
+    ctx: &Context,
+) -> Result<SyscallReturn> {
+    let offset = (((offset_high as u64) << 32) | offset_low as u64) as i64;
+    let SyscallReturn::Return(new_offset) = sys_lseek(fd, offset as isize, whence, ctx)? else {
+        unreachable!("lseek always returns a value");
+    };
+    ctx.user_space()
+        .write_val(result_addr, &(new_offset as i64))?;
+    Ok(SyscallReturn::Return(0))
+}
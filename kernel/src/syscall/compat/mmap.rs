@@ -0,0 +1,31 @@
+// SPDX-License-Identifier: MPL-2.0
+
+use crate::{
+    prelude::*,
+    syscall::{mmap::sys_mmap, SyscallReturn},
+};
+
+/// The unit of the offsets of `mmap2`, which is 4096 bytes regardless of the page size.
+const MMAP2_OFFSET_UNIT: u64 = 4096;
+
+/// Maps the memory with the offset in units of 4096 bytes, so that the 32-bit programs can map
+/// the files that are larger than 4 GiB.
+pub(super) fn sys_mmap2(
+    addr: u64,
+    len: u64,
+    perms: u64,
+    flags: u64,
+    fd: u64,
+    page_offset: u64,
+    ctx: &Context,
+) -> Result<SyscallReturn> {
+    sys_mmap(
+        addr,
+        len,
+        perms,
+        flags,
+        fd,
+        page_offset * MMAP2_OFFSET_UNIT,
+        ctx,
+    )
+}
@@ -0,0 +1,185 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! The system calls of the 32-bit (i386) programs.
+//!
+//! The 32-bit programs make system calls with `int 0x80`, using the i386 system call numbers.
+//! Most system calls share the implementation with their 64-bit counterparts, since the
+//! arguments are zero-extended to 64 bits. The system calls whose arguments are signed, or whose
+//! user-space structures have different layouts (e.g., `stat64`, `timespec`, and `iovec`), are
+//! translated by the handlers in this module.
+//!
+//! The 32-bit C libraries also require the 32-bit signal frames (see
+//! [`crate::process::signal::compat`]) and the thread-local storage segments, which are set up by
+//! `set_thread_area`.
+//!
+//! TODO: Support `clone` with the 32-bit thread-local storage descriptors, which is required to
+//! create threads.
+
+mod ioctl;
+mod lseek;
+mod mmap;
+mod signal;
+mod stat;
+mod thread_area;
+mod time;
+mod uio;
+mod wait;
+
+use self::{
+    ioctl::sys_compat_ioctl,
+    lseek::{sys_compat_lseek, sys_llseek},
+    mmap::sys_mmap2,
+    signal::{sys_compat_rt_sigaction, sys_compat_rt_sigreturn, sys_sigreturn},
+    stat::{sys_fstat64, sys_fstatat64, sys_lstat64, sys_stat64},
+    thread_area::{sys_get_thread_area, sys_set_thread_area},
+    time::{
+        sys_compat_clock_gettime, sys_compat_gettimeofday, sys_compat_nanosleep, sys_compat_time,
+    },
+    uio::{sys_compat_readv, sys_compat_writev},
+    wait::sys_waitpid,
+};
+use super::{
+    access::{sys_access, sys_faccessat},
+    brk::sys_brk,
+    chdir::{sys_chdir, sys_fchdir},
+    chmod::{sys_chmod, sys_fchmod, sys_fchmodat},
+    chroot::sys_chroot,
+    clock_gettime::sys_clock_gettime,
+    close::sys_close,
+    dup::{sys_dup, sys_dup2, sys_dup3},
+    execve::sys_execve,
+    exit::sys_exit,
+    exit_group::sys_exit_group,
+    fork::{sys_fork, sys_vfork},
+    fsync::sys_fdatasync,
+    getcpu::sys_getcpu,
+    getcwd::sys_getcwd,
+    getdents64::sys_getdents64,
+    getegid::sys_getegid,
+    geteuid::sys_geteuid,
+    getgid::sys_getgid,
+    getpgrp::sys_getpgrp,
+    getpid::sys_getpid,
+    getppid::sys_getppid,
+    getrandom::sys_getrandom,
+    getsid::sys_getsid,
+    gettid::sys_gettid,
+    getuid::sys_getuid,
+    impl_syscall_nums_and_dispatch_fn,
+    kill::sys_kill,
+    link::{sys_link, sys_linkat},
+    mkdir::{sys_mkdir, sys_mkdirat},
+    mprotect::sys_mprotect,
+    munmap::sys_munmap,
+    open::{sys_open, sys_openat},
+    pipe::{sys_pipe, sys_pipe2},
+    poll::sys_poll,
+    read::sys_read,
+    readlink::{sys_readlink, sys_readlinkat},
+    rename::{sys_rename, sys_renameat},
+    rmdir::sys_rmdir,
+    rt_sigprocmask::sys_rt_sigprocmask,
+    sched_yield::sys_sched_yield,
+    set_tid_address::sys_set_tid_address,
+    setgid::sys_setgid,
+    setpgid::sys_setpgid,
+    setsid::sys_setsid,
+    setuid::sys_setuid,
+    symlink::{sys_symlink, sys_symlinkat},
+    sync::sys_sync,
+    tgkill::sys_tgkill,
+    umask::sys_umask,
+    uname::sys_uname,
+    unlink::{sys_unlink, sys_unlinkat},
+    write::sys_write,
+};
+
+impl_syscall_nums_and_dispatch_fn! {
+    SYS_EXIT = 1               => sys_exit(args[..1]);
+    SYS_FORK = 2               => sys_fork(args[..0], &user_ctx);
+    SYS_READ = 3               => sys_read(args[..3]);
+    SYS_WRITE = 4              => sys_write(args[..3]);
+    SYS_OPEN = 5               => sys_open(args[..3]);
+    SYS_CLOSE = 6              => sys_close(args[..1]);
+    SYS_WAITPID = 7            => sys_waitpid(args[..3]);
+    SYS_LINK = 9               => sys_link(args[..2]);
+    SYS_UNLINK = 10            => sys_unlink(args[..1]);
+    SYS_EXECVE = 11            => sys_execve(args[..3], &mut user_ctx);
+    SYS_CHDIR = 12             => sys_chdir(args[..1]);
+    SYS_TIME = 13              => sys_compat_time(args[..1]);
+    SYS_CHMOD = 15             => sys_chmod(args[..2]);
+    SYS_LSEEK = 19             => sys_compat_lseek(args[..3]);
+    SYS_GETPID = 20            => sys_getpid(args[..0]);
+    SYS_ACCESS = 33            => sys_access(args[..2]);
+    SYS_SYNC = 36              => sys_sync(args[..0]);
+    SYS_KILL = 37              => sys_kill(args[..2]);
+    SYS_RENAME = 38            => sys_rename(args[..2]);
+    SYS_MKDIR = 39             => sys_mkdir(args[..2]);
+    SYS_RMDIR = 40             => sys_rmdir(args[..1]);
+    SYS_DUP = 41               => sys_dup(args[..1]);
+    SYS_PIPE = 42              => sys_pipe(args[..1]);
+    SYS_BRK = 45               => sys_brk(args[..1]);
+    SYS_IOCTL = 54             => sys_compat_ioctl(args[..3]);
+    SYS_SETPGID = 57           => sys_setpgid(args[..2]);
+    SYS_UMASK = 60             => sys_umask(args[..1]);
+    SYS_CHROOT = 61            => sys_chroot(args[..1]);
+    SYS_DUP2 = 63              => sys_dup2(args[..2]);
+    SYS_GETPPID = 64           => sys_getppid(args[..0]);
+    SYS_GETPGRP = 65           => sys_getpgrp(args[..0]);
+    SYS_SETSID = 66            => sys_setsid(args[..0]);
+    SYS_GETTIMEOFDAY = 78      => sys_compat_gettimeofday(args[..1]);
+    SYS_SYMLINK = 83           => sys_symlink(args[..2]);
+    SYS_READLINK = 85          => sys_readlink(args[..3]);
+    SYS_MUNMAP = 91            => sys_munmap(args[..2]);
+    SYS_FCHMOD = 94            => sys_fchmod(args[..2]);
+    SYS_SIGRETURN = 119        => sys_sigreturn(args[..0], &mut user_ctx);
+    SYS_UNAME = 122            => sys_uname(args[..1]);
+    SYS_MPROTECT = 125         => sys_mprotect(args[..3]);
+    SYS_FCHDIR = 133           => sys_fchdir(args[..1]);
+    SYS_LLSEEK = 140           => sys_llseek(args[..5]);
+    SYS_READV = 145            => sys_compat_readv(args[..3]);
+    SYS_WRITEV = 146           => sys_compat_writev(args[..3]);
+    SYS_GETSID = 147           => sys_getsid(args[..1]);
+    SYS_FDATASYNC = 148        => sys_fdatasync(args[..1]);
+    SYS_SCHED_YIELD = 158      => sys_sched_yield(args[..0]);
+    SYS_NANOSLEEP = 162        => sys_compat_nanosleep(args[..2]);
+    SYS_POLL = 168             => sys_poll(args[..3]);
+    SYS_RT_SIGRETURN = 173     => sys_compat_rt_sigreturn(args[..0], &mut user_ctx);
+    SYS_RT_SIGACTION = 174     => sys_compat_rt_sigaction(args[..4]);
+    SYS_RT_SIGPROCMASK = 175   => sys_rt_sigprocmask(args[..4]);
+    SYS_GETCWD = 183           => sys_getcwd(args[..2]);
+    SYS_VFORK = 190            => sys_vfork(args[..0], &user_ctx);
+    SYS_MMAP2 = 192            => sys_mmap2(args[..6]);
+    SYS_STAT64 = 195           => sys_stat64(args[..2]);
+    SYS_LSTAT64 = 196          => sys_lstat64(args[..2]);
+    SYS_FSTAT64 = 197          => sys_fstat64(args[..2]);
+    SYS_GETUID32 = 199         => sys_getuid(args[..0]);
+    SYS_GETGID32 = 200         => sys_getgid(args[..0]);
+    SYS_GETEUID32 = 201        => sys_geteuid(args[..0]);
+    SYS_GETEGID32 = 202        => sys_getegid(args[..0]);
+    SYS_SETUID32 = 213         => sys_setuid(args[..1]);
+    SYS_SETGID32 = 214         => sys_setgid(args[..1]);
+    SYS_GETDENTS64 = 220       => sys_getdents64(args[..3]);
+    SYS_GETTID = 224           => sys_gettid(args[..0]);
+    SYS_SET_THREAD_AREA = 243  => sys_set_thread_area(args[..1], &mut user_ctx);
+    SYS_GET_THREAD_AREA = 244  => sys_get_thread_area(args[..1], &user_ctx);
+    SYS_EXIT_GROUP = 252       => sys_exit_group(args[..1]);
+    SYS_SET_TID_ADDRESS = 258  => sys_set_tid_address(args[..1]);
+    SYS_CLOCK_GETTIME = 265    => sys_compat_clock_gettime(args[..2]);
+    SYS_TGKILL = 270           => sys_tgkill(args[..3]);
+    SYS_OPENAT = 295           => sys_openat(args[..4]);
+    SYS_MKDIRAT = 296          => sys_mkdirat(args[..3]);
+    SYS_FSTATAT64 = 300        => sys_fstatat64(args[..4]);
+    SYS_UNLINKAT = 301         => sys_unlinkat(args[..3]);
+    SYS_RENAMEAT = 302         => sys_renameat(args[..4]);
+    SYS_LINKAT = 303           => sys_linkat(args[..5]);
+    SYS_SYMLINKAT = 304        => sys_symlinkat(args[..3]);
+    SYS_READLINKAT = 305       => sys_readlinkat(args[..4]);
+    SYS_FCHMODAT = 306         => sys_fchmodat(args[..3]);
+    SYS_FACCESSAT = 307        => sys_faccessat(args[..3]);
+    SYS_GETCPU = 318           => sys_getcpu(args[..3]);
+    SYS_DUP3 = 330             => sys_dup3(args[..3]);
+    SYS_PIPE2 = 331            => sys_pipe2(args[..2]);
+    SYS_GETRANDOM = 355        => sys_getrandom(args[..3]);
+    SYS_CLOCK_GETTIME64 = 403  => sys_clock_gettime(args[..2]);
+}
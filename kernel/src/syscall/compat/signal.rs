@@ -0,0 +1,97 @@
+// SPDX-License-Identifier: MPL-2.0
+
+use ostd::cpu::UserContext;
+
+use crate::{
+    prelude::*,
+    process::signal::{
+        c_types::sigaction_t,
+        compat::{restore_frame, restore_rt_frame},
+        sig_num::SigNum,
+    },
+    syscall::{rt_sigaction::do_rt_sigaction, SyscallReturn},
+};
+
+/// The `sigaction` structure of the 32-bit programs.
+#[repr(C)]
+#[derive(Debug, Default, Clone, Copy, Pod)]
+struct CompatSigaction {
+    handler: u32,
+    flags: u32,
+    restorer: u32,
+    mask: [u32; 2],
+}
+
+impl From<CompatSigaction> for sigaction_t {
+    fn from(action: CompatSigaction) -> Self {
+        Self {
+            handler_ptr: action.handler as Vaddr,
+            flags: action.flags,
+            restorer_ptr: action.restorer as Vaddr,
+            mask: (action.mask[1] as u64) << 32 | action.mask[0] as u64,
+        }
+    }
+}
+
+impl From<sigaction_t> for CompatSigaction {
+    fn from(action: sigaction_t) -> Self {
+        Self {
+            handler: action.handler_ptr as u32,
+            flags: action.flags,
+            restorer: action.restorer_ptr as u32,
+            mask: [action.mask as u32, (action.mask >> 32) as u32],
+        }
+    }
+}
+
+pub(super) fn sys_compat_rt_sigaction(
+    sig_num: u8,
+    sig_action_addr: Vaddr,
+    old_sig_action_addr: Vaddr,
+    sigset_size: u64,
+    ctx: &Context,
+) -> Result<SyscallReturn> {
+    let sig_num = SigNum::try_from(sig_num)?;
+    debug!(
+        "signal = {}, sig_action_addr = 0x{:x}, old_sig_action_addr = 0x{:x}, sigset_size = {}",
+        sig_num.sig_name(),
+        sig_action_addr,
+        old_sig_action_addr,
+        sigset_size
+    );
+
+    if sigset_size != 8 {
+        return_errno_with_message!(Errno::EINVAL, "sigset size is not equal to 8");
+    }
+
+    let sig_action_c = if sig_action_addr != 0 {
+        let compat_action = ctx
+            .user_space()
+            .read_val::<CompatSigaction>(sig_action_addr)?;
+        Some(sigaction_t::from(compat_action))
+    } else {
+        None
+    };
+
+    let old_action_c = do_rt_sigaction(sig_num, sig_action_c, ctx);
+
+    if old_sig_action_addr != 0 {
+        ctx.user_space()
+            .write_val(old_sig_action_addr, &CompatSigaction::from(old_action_c))?;
+    }
+
+    Ok(SyscallReturn::Return(0))
+}
+
+pub(super) fn sys_compat_rt_sigreturn(
+    ctx: &Context,
+    user_ctx: &mut UserContext,
+) -> Result<SyscallReturn> {
+    restore_rt_frame(ctx, user_ctx)?;
+    Ok(SyscallReturn::NoReturn)
+}
+
+pub(super) fn sys_sigreturn(ctx: &Context, user_ctx: &mut UserContext) -> Result<SyscallReturn> {
+    restore_frame(ctx, user_ctx)?;
+    Ok(SyscallReturn::NoReturn)
+}
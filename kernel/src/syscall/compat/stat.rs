@@ -0,0 +1,125 @@
+// SPDX-License-Identifier: MPL-2.0
+
+use crate::{
+    fs::{file_table::FileDesc, fs_resolver::AT_FDCWD, utils::Metadata},
+    prelude::*,
+    syscall::{
+        stat::{fstat_metadata, fstatat_metadata, StatFlags},
+        SyscallReturn,
+    },
+};
+
+pub(super) fn sys_fstat64(
+    fd: FileDesc,
+    stat_buf_ptr: Vaddr,
+    ctx: &Context,
+) -> Result<SyscallReturn> {
+    debug!("fd = {}, stat_buf_addr = 0x{:x}", fd, stat_buf_ptr);
+
+    let stat = Stat64::from(fstat_metadata(fd, ctx)?);
+    ctx.user_space().write_val(stat_buf_ptr, &stat)?;
+    Ok(SyscallReturn::Return(0))
+}
+
+pub(super) fn sys_stat64(
+    filename_ptr: Vaddr,
+    stat_buf_ptr: Vaddr,
+    ctx: &Context,
+) -> Result<SyscallReturn> {
+    self::sys_fstatat64(AT_FDCWD, filename_ptr, stat_buf_ptr, 0, ctx)
+}
+
+pub(super) fn sys_lstat64(
+    filename_ptr: Vaddr,
+    stat_buf_ptr: Vaddr,
+    ctx: &Context,
+) -> Result<SyscallReturn> {
+    self::sys_fstatat64(
+        AT_FDCWD,
+        filename_ptr,
+        stat_buf_ptr,
+        StatFlags::AT_SYMLINK_NOFOLLOW.bits(),
+        ctx,
+    )
+}
+
+pub(super) fn sys_fstatat64(
+    dirfd: FileDesc,
+    filename_ptr: Vaddr,
+    stat_buf_ptr: Vaddr,
+    flags: u32,
+    ctx: &Context,
+) -> Result<SyscallReturn> {
+    debug!("stat_buf_ptr = 0x{:x}", stat_buf_ptr);
+
+    let stat = Stat64::from(fstatat_metadata(dirfd, filename_ptr, flags, ctx)?);
+    ctx.user_space().write_val(stat_buf_ptr, &stat)?;
+    Ok(SyscallReturn::Return(0))
+}
+
+/// File Stat of the 32-bit programs.
+///
+/// Reference: <https://elixir.bootlin.com/linux/v6.10/source/arch/x86/include/uapi/asm/stat.h#L55>
+#[derive(Debug, Clone, Copy, Pod)]
+#[repr(C, packed)]
+struct Stat64 {
+    /// ID of device containing file
+    st_dev: u64,
+    __pad0: [u8; 4],
+    /// The lower 32 bits of the inode number
+    __st_ino: u32,
+    /// File type and mode
+    st_mode: u32,
+    /// Number of hard links
+    st_nlink: u32,
+    /// User ID of owner
+    st_uid: u32,
+    /// Group ID of owner
+    st_gid: u32,
+    /// Device ID (if special file)
+    st_rdev: u64,
+    __pad3: [u8; 4],
+    /// Total size, in bytes
+    st_size: i64,
+    /// Block size for filesystem I/O
+    st_blksize: u32,
+    /// Number of 512-byte blocks allocated
+    st_blocks: u64,
+    /// Time of last access
+    st_atime: u32,
+    st_atime_nsec: u32,
+    /// Time of last modification
+    st_mtime: u32,
+    st_mtime_nsec: u32,
+    /// Time of last status change
+    st_ctime: u32,
+    st_ctime_nsec: u32,
+    /// Inode number
+    st_ino: u64,
+}
+
+impl From<Metadata> for Stat64 {
+    fn from(info: Metadata) -> Self {
+        Self {
+            st_dev: info.dev,
+            __pad0: [0; 4],
+            __st_ino: info.ino as u32,
+            st_mode: info.type_ as u32 | info.mode.bits() as u32,
+            st_nlink: info.nlinks as u32,
+            st_uid: info.uid.into(),
+            st_gid: info.gid.into(),
+            st_rdev: info.rdev,
+            __pad3: [0; 4],
+            st_size: info.size as i64,
+            st_blksize: info.blk_size as u32,
+            st_blocks: (info.blocks * (info.blk_size / 512)) as u64, // Number of 512B blocks
+            st_atime: info.atime.as_secs() as u32,
+            st_atime_nsec: info.atime.subsec_nanos(),
+            st_mtime: info.mtime.as_secs() as u32,
+            st_mtime_nsec: info.mtime.subsec_nanos(),
+            st_ctime: info.ctime.as_secs() as u32,
+            st_ctime_nsec: info.ctime.subsec_nanos(),
+            st_ino: info.ino,
+        }
+    }
+}
@@ -0,0 +1,187 @@
+// SPDX-License-Identifier: MPL-2.0
+
+use ostd::cpu::{TlsSegment, TlsSegmentFlags, UserContext, GDT_ENTRY_TLS_MIN, NR_TLS_SEGMENTS};
+
+use crate::{prelude::*, syscall::SyscallReturn};
+
+/// The `user_desc` structure, which describes a thread-local storage segment.
+#[repr(C)]
+#[derive(Debug, Default, Clone, Copy, Pod)]
+struct UserDesc {
+    entry_number: u32,
+    base_addr: u32,
+    limit: u32,
+    flags: u32,
+}
+
+bitflags! {
+    /// The bit fields of [`UserDesc`].
+    struct UserDescFlags: u32 {
+        const SEG_32BIT       = 1 << 0;
+        // `contents` has two bits. The first bit means that the data segment expands down,
+        // and the second bit means that the segment is a code segment.
+        const EXPAND_DOWN     = 1 << 1;
+        const CODE            = 1 << 2;
+        const READ_EXEC_ONLY  = 1 << 3;
+        const LIMIT_IN_PAGES  = 1 << 4;
+        const SEG_NOT_PRESENT = 1 << 5;
+        const USEABLE         = 1 << 6;
+    }
+}
+
+impl UserDesc {
+    fn new(entry_number: u32, segment: Option<TlsSegment>) -> Self {
+        let Some(segment) = segment else {
+            return Self {
+                entry_number,
+                flags: (UserDescFlags::READ_EXEC_ONLY | UserDescFlags::SEG_NOT_PRESENT).bits(),
+                ..Default::default()
+            };
+        };
+
+        let mut flags = UserDescFlags::empty();
+        flags.set(
+            UserDescFlags::SEG_32BIT,
+            segment.flags.contains(TlsSegmentFlags::DEFAULT_SIZE),
+        );
+        flags.set(
+            UserDescFlags::EXPAND_DOWN,
+            segment.flags.contains(TlsSegmentFlags::EXPAND_DOWN),
+        );
+        flags.set(
+            UserDescFlags::READ_EXEC_ONLY,
+            !segment.flags.contains(TlsSegmentFlags::WRITABLE),
+        );
+        flags.set(
+            UserDescFlags::LIMIT_IN_PAGES,
+            segment.flags.contains(TlsSegmentFlags::GRANULARITY),
+        );
+        flags.set(
+            UserDescFlags::SEG_NOT_PRESENT,
+            !segment.flags.contains(TlsSegmentFlags::PRESENT),
+        );
+        flags.set(
+            UserDescFlags::USEABLE,
+            segment.flags.contains(TlsSegmentFlags::AVAILABLE),
+        );
+
+        Self {
+            entry_number,
+            base_addr: segment.base,
+            limit: segment.limit,
+            flags: flags.bits(),
+        }
+    }
+
+    /// Converts the description to a segment.
+    ///
+    /// The empty description, which clears the segment, is converted to `None`.
+    fn to_segment(self) -> Result<Option<TlsSegment>> {
+        let flags = UserDescFlags::from_bits_truncate(self.flags);
+
+        // Like Linux, both the description with all fields cleared and the description that is
+        // returned for a cleared segment are empty.
+        let is_empty = self.base_addr == 0
+            && self.limit == 0
+            && (flags.is_empty()
+                || flags == UserDescFlags::READ_EXEC_ONLY | UserDescFlags::SEG_NOT_PRESENT);
+        if is_empty {
+            return Ok(None);
+        }
+
+        if !flags.contains(UserDescFlags::SEG_32BIT) {
+            return_errno_with_message!(Errno::EINVAL, "the segment is not a 32-bit segment");
+        }
+        if flags.contains(UserDescFlags::CODE) {
+            return_errno_with_message!(Errno::EINVAL, "only data segments are allowed");
+        }
+
+        let mut segment_flags = TlsSegmentFlags::DEFAULT_SIZE;
+        segment_flags.set(
+            TlsSegmentFlags::EXPAND_DOWN,
+            flags.contains(UserDescFlags::EXPAND_DOWN),
+        );
+        segment_flags.set(
+            TlsSegmentFlags::WRITABLE,
+            !flags.contains(UserDescFlags::READ_EXEC_ONLY),
+        );
+        segment_flags.set(
+            TlsSegmentFlags::GRANULARITY,
+            flags.contains(UserDescFlags::LIMIT_IN_PAGES),
+        );
+        segment_flags.set(
+            TlsSegmentFlags::PRESENT,
+            !flags.contains(UserDescFlags::SEG_NOT_PRESENT),
+        );
+        segment_flags.set(
+            TlsSegmentFlags::AVAILABLE,
+            flags.contains(UserDescFlags::USEABLE),
+        );
+
+        Ok(Some(TlsSegment {
+            base: self.base_addr,
+            limit: self.limit & 0xf_ffff,
+            flags: segment_flags,
+        }))
+    }
+}
+
+pub(super) fn sys_set_thread_area(
+    u_info_addr: Vaddr,
+    ctx: &Context,
+    user_ctx: &mut UserContext,
+) -> Result<SyscallReturn> {
+    let u_info = ctx.user_space().read_val::<UserDesc>(u_info_addr)?;
+    debug!("u_info = {:?}", u_info);
+
+    let segment = u_info.to_segment()?;
+
+    // Like Linux, the entry number of -1 means allocating a free entry.
+    let entry_number = if u_info.entry_number == u32::MAX {
+        let Some(index) = (0..NR_TLS_SEGMENTS).find(|index| user_ctx.tls_segment(*index).is_none())
+        else {
+            return_errno_with_message!(Errno::ESRCH, "no free thread-local storage entries");
+        };
+        let entry_number = (GDT_ENTRY_TLS_MIN + index) as u32;
+        ctx.user_space().write_val(
+            u_info_addr + core::mem::offset_of!(UserDesc, entry_number),
+            &entry_number,
+        )?;
+        entry_number
+    } else {
+        u_info.entry_number
+    };
+
+    let index = tls_index(entry_number)?;
+    user_ctx.set_tls_segment(index, segment);
+
+    Ok(SyscallReturn::Return(0))
+}
+
+pub(super) fn sys_get_thread_area(
+    u_info_addr: Vaddr,
+    ctx: &Context,
+    user_ctx: &UserContext,
+) -> Result<SyscallReturn> {
+    let entry_number = ctx.user_space().read_val::<u32>(u_info_addr)?;
+    debug!("entry_number = {}", entry_number);
+
+    let index = tls_index(entry_number)?;
+    let u_info = UserDesc::new(entry_number, user_ctx.tls_segment(index));
+    ctx.user_space().write_val(u_info_addr, &u_info)?;
+
+    Ok(SyscallReturn::Return(0))
+}
+
+/// Converts the entry number of the GDT to the index of the thread-local storage segments.
+fn tls_index(entry_number: u32) -> Result<usize> {
+    (entry_number as usize)
+        .checked_sub(GDT_ENTRY_TLS_MIN)
+        .filter(|index| *index < NR_TLS_SEGMENTS)
+        .ok_or_else(|| {
+            Error::with_message(
+                Errno::EINVAL,
+                "the entry is not a thread-local storage entry",
+            )
+        })
+}
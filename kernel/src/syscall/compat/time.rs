@@ -0,0 +1,121 @@
+// SPDX-License-Identifier: MPL-2.0
+
+use core::time::Duration;
+
+use crate::{
+    prelude::*,
+    syscall::{clock_gettime::read_clock, nanosleep::do_clock_nanosleep, ClockId, SyscallReturn},
+    time::{clockid_t, timespec_t, SystemTime},
+};
+
+/// The `timespec` structure of the 32-bit programs.
+#[repr(C)]
+#[derive(Debug, Default, Clone, Copy, Pod)]
+struct CompatTimespec {
+    sec: i32,
+    nsec: i32,
+}
+
+impl From<Duration> for CompatTimespec {
+    fn from(duration: Duration) -> Self {
+        Self {
+            sec: duration.as_secs() as i32,
+            nsec: duration.subsec_nanos() as i32,
+        }
+    }
+}
+
+impl TryFrom<CompatTimespec> for Duration {
+    type Error = Error;
+
+    fn try_from(value: CompatTimespec) -> Result<Self> {
+        Duration::try_from(timespec_t {
+            sec: value.sec as _,
+            nsec: value.nsec as _,
+        })
+    }
+}
+
+/// The `timeval` structure of the 32-bit programs.
+#[repr(C)]
+#[derive(Debug, Default, Clone, Copy, Pod)]
+struct CompatTimeval {
+    sec: i32,
+    usec: i32,
+}
+
+impl From<Duration> for CompatTimeval {
+    fn from(duration: Duration) -> Self {
+        Self {
+            sec: duration.as_secs() as i32,
+            usec: duration.subsec_micros() as i32,
+        }
+    }
+}
+
+pub(super) fn sys_compat_time(tloc: Vaddr, ctx: &Context) -> Result<SyscallReturn> {
+    debug!("tloc = 0x{tloc:x}");
+
+    let now_as_secs = SystemTime::now()
+        .duration_since(&SystemTime::UNIX_EPOCH)?
+        .as_secs() as i32;
+
+    if tloc != 0 {
+        ctx.user_space().write_val(tloc, &now_as_secs)?;
+    }
+
+    Ok(SyscallReturn::Return(now_as_secs as _))
+}
+
+pub(super) fn sys_compat_gettimeofday(timeval_addr: Vaddr, ctx: &Context) -> Result<SyscallReturn> {
+    if timeval_addr == 0 {
+        return Ok(SyscallReturn::Return(0));
+    }
+
+    let now = SystemTime::now().duration_since(&SystemTime::UNIX_EPOCH)?;
+    ctx.user_space()
+        .write_val(timeval_addr, &CompatTimeval::from(now))?;
+
+    Ok(SyscallReturn::Return(0))
+}
+
+pub(super) fn sys_compat_clock_gettime(
+    clockid: clockid_t,
+    timespec_addr: Vaddr,
+    ctx: &Context,
+) -> Result<SyscallReturn> {
+    debug!("clockid = {:?}", clockid);
+
+    let time_duration = read_clock(clockid, ctx)?;
+    ctx.user_space()
+        .write_val(timespec_addr, &CompatTimespec::from(time_duration))?;
+
+    Ok(SyscallReturn::Return(0))
+}
+
+pub(super) fn sys_compat_nanosleep(
+    request_timespec_addr: Vaddr,
+    remain_timespec_addr: Vaddr,
+    ctx: &Context,
+) -> Result<SyscallReturn> {
+    let request_time = Duration::try_from(
+        ctx.user_space()
+            .read_val::<CompatTimespec>(request_timespec_addr)?,
+    )?;
+
+    do_clock_nanosleep(
+        ClockId::CLOCK_MONOTONIC as clockid_t,
+        false,
+        request_time,
+        |remaining_duration| {
+            if remain_timespec_addr == 0 {
+                return Ok(());
+            }
+            ctx.user_space().write_val(
+                remain_timespec_addr,
+                &CompatTimespec::from(remaining_duration),
+            )
+        },
+        ctx,
+    )
+}
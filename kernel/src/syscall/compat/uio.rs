@@ -0,0 +1,74 @@
+// SPDX-License-Identifier: MPL-2.0
+
+use crate::{
+    fs::file_table::{get_file_fast, FileDesc},
+    prelude::*,
+    syscall::SyscallReturn,
+    util::{VmReaderArray, VmWriterArray},
+};
+
+pub(super) fn sys_compat_readv(
+    fd: FileDesc,
+    io_vec_ptr: Vaddr,
+    io_vec_count: usize,
+    ctx: &Context,
+) -> Result<SyscallReturn> {
+    debug!(
+        "fd = {}, io_vec_ptr = 0x{:x}, io_vec_counter = 0x{:x}",
+        fd, io_vec_ptr, io_vec_count
+    );
+
+    let mut file_table = ctx.thread_local.file_table().borrow_mut();
+    let file = get_file_fast!(&mut file_table, fd);
+
+    let mut total_len: usize = 0;
+
+    let user_space = ctx.user_space();
+    let mut writer_array =
+        VmWriterArray::from_user_compat_io_vecs(&user_space, io_vec_ptr, io_vec_count)?;
+    for writer in writer_array.writers_mut() {
+        if !writer.has_avail() {
+            continue;
+        }
+
+        let read_len = file.read(writer)?;
+        total_len += read_len;
+        if read_len == 0 || writer.has_avail() {
+            // End of file reached or no more data to read
+            break;
+        }
+    }
+
+    Ok(SyscallReturn::Return(total_len as _))
+}
+
+pub(super) fn sys_compat_writev(
+    fd: FileDesc,
+    io_vec_ptr: Vaddr,
+    io_vec_count: usize,
+    ctx: &Context,
+) -> Result<SyscallReturn> {
+    debug!(
+        "fd = {}, io_vec_ptr = 0x{:x}, io_vec_counter = 0x{:x}",
+        fd, io_vec_ptr, io_vec_count
+    );
+
+    let mut file_table = ctx.thread_local.file_table().borrow_mut();
+    let file = get_file_fast!(&mut file_table, fd);
+
+    let mut total_len: usize = 0;
+
+    let user_space = ctx.user_space();
+    let mut reader_array =
+        VmReaderArray::from_user_compat_io_vecs(&user_space, io_vec_ptr, io_vec_count)?;
+    for reader in reader_array.readers_mut() {
+        if !reader.has_remain() {
+            continue;
+        }
+
+        let write_len = file.write(reader)?;
+        total_len += write_len;
+    }
+
+    Ok(SyscallReturn::Return(total_len as _))
+}
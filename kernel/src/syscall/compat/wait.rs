@@ -0,0 +1,15 @@
+// SPDX-License-Identifier: MPL-2.0
+
+use crate::{
+    prelude::*,
+    syscall::{wait4::sys_wait4, SyscallReturn},
+};
+
+pub(super) fn sys_waitpid(
+    wait_pid: u64,
+    exit_status_ptr: u64,
+    wait_options: u32,
+    ctx: &Context,
+) -> Result<SyscallReturn> {
+    sys_wait4(wait_pid, exit_status_ptr, wait_options, 0, ctx)
+}
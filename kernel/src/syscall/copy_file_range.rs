@@ -0,0 +1,107 @@
+// SPDX-License-Identifier: MPL-2.0
+
+use super::SyscallReturn;
+use crate::{
+    fs::{
+        file_handle::FileLike,
+        file_table::{FileDesc, WithFileTable},
+        splice::{splice, SpliceEnd},
+        utils::{InodeType, SeekFrom, StatusFlags},
+    },
+    prelude::*,
+};
+
+pub fn sys_copy_file_range(
+    fd_in: FileDesc,
+    off_in_ptr: Vaddr,
+    fd_out: FileDesc,
+    off_out_ptr: Vaddr,
+    len: usize,
+    flags: u32,
+    ctx: &Context,
+) -> Result<SyscallReturn> {
+    debug!(
+        "fd_in = {}, off_in_ptr = 0x{:x}, fd_out = {}, off_out_ptr = 0x{:x}, len = 0x{:x}, flags = 0x{:x}",
+        fd_in, off_in_ptr, fd_out, off_out_ptr, len, flags
+    );
+
+    if flags != 0 {
+        return_errno_with_message!(Errno::EINVAL, "the flags must be zero");
+    }
+
+    let (in_file, out_file) = ctx
+        .thread_local
+        .file_table()
+        .borrow_mut()
+        .read_with(|inner| {
+            let in_file = inner.get_file(fd_in)?.clone();
+            let out_file = inner.get_file(fd_out)?.clone();
+            Ok::<_, Error>((in_file, out_file))
+        })?;
+
+    check_regular_file(&in_file)?;
+    check_regular_file(&out_file)?;
+    if out_file.status_flags().contains(StatusFlags::O_APPEND) {
+        return_errno_with_message!(Errno::EBADF, "the output file is opened in append mode");
+    }
+
+    let mut off_in = read_offset(off_in_ptr, ctx)?;
+    let mut off_out = read_offset(off_out_ptr, ctx)?;
+
+    if len == 0 {
+        return Ok(SyscallReturn::Return(0));
+    }
+
+    // The source and destination ranges must not overlap if they are in the same file.
+    let in_inode = in_file.as_inode_or_err()?.dentry().inode();
+    let out_inode = out_file.as_inode_or_err()?.dentry().inode();
+    if Arc::ptr_eq(in_inode, out_inode) {
+        let in_pos = match off_in {
+            Some(off_in) => off_in,
+            None => in_file.seek(SeekFrom::Current(0))?,
+        };
+        let out_pos = match off_out {
+            Some(off_out) => off_out,
+            None => out_file.seek(SeekFrom::Current(0))?,
+        };
+        if in_pos < out_pos.saturating_add(len) && out_pos < in_pos.saturating_add(len) {
+            return_errno_with_message!(Errno::EINVAL, "the ranges overlap in the same file");
+        }
+    }
+
+    let copied_len = splice(
+        SpliceEnd::new(&in_file, off_in.as_mut()),
+        SpliceEnd::new(&out_file, off_out.as_mut()),
+        len,
+        false,
+    )?;
+
+    if let Some(off_in) = off_in {
+        ctx.user_space().write_val(off_in_ptr, &(off_in as i64))?;
+    }
+    if let Some(off_out) = off_out {
+        ctx.user_space().write_val(off_out_ptr, &(off_out as i64))?;
+    }
+
+    Ok(SyscallReturn::Return(copied_len as _))
+}
+
+fn check_regular_file(file: &Arc<dyn FileLike>) -> Result<()> {
+    match file.metadata().type_ {
+        InodeType::File => Ok(()),
+        InodeType::Dir => return_errno_with_message!(Errno::EISDIR, "the file is a directory"),
+        _ => return_errno_with_message!(Errno::EINVAL, "the file is not a regular file"),
+    }
+}
+
+fn read_offset(offset_ptr: Vaddr, ctx: &Context) -> Result<Option<usize>> {
+    if offset_ptr == 0 {
+        return Ok(None);
+    }
+
+    let offset: i64 = ctx.user_space().read_val(offset_ptr)?;
+    if offset < 0 {
+        return_errno_with_message!(Errno::EINVAL, "the offset cannot be negative");
+    }
+    Ok(Some(offset as usize))
+}
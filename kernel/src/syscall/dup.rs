@@ -2,17 +2,18 @@
 
 use super::SyscallReturn;
 use crate::{
-    fs::file_table::{get_file_fast, FdFlags, FileDesc},
+    fs::file_table::{get_file_fast, max_fds, FdFlags, FileDesc},
     prelude::*,
-    process::ResourceType,
 };
 
 pub fn sys_dup(old_fd: FileDesc, ctx: &Context) -> Result<SyscallReturn> {
     debug!("old_fd = {}", old_fd);
 
+    let max_fds = max_fds(&ctx.process);
+
     let file_table = ctx.thread_local.file_table().borrow();
     let mut file_table_locked = file_table.write();
-    let new_fd = file_table_locked.dup(old_fd, 0, FdFlags::empty())?;
+    let new_fd = file_table_locked.dup(old_fd, 0, FdFlags::empty(), max_fds)?;
 
     Ok(SyscallReturn::Return(new_fd as _))
 }
@@ -56,21 +57,15 @@ fn do_dup3(
         return_errno!(Errno::EINVAL);
     }
 
-    if new_fd
-        >= ctx
-            .process
-            .resource_limits()
-            .lock()
-            .get_rlimit(ResourceType::RLIMIT_NOFILE)
-            .get_cur() as FileDesc
-    {
+    let max_fds = max_fds(&ctx.process);
+    if new_fd as usize >= max_fds {
         return_errno!(Errno::EBADF);
     }
 
     let file_table = ctx.thread_local.file_table().borrow();
     let mut file_table_locked = file_table.write();
     let _ = file_table_locked.close_file(new_fd);
-    let new_fd = file_table_locked.dup(old_fd, new_fd, flags)?;
+    let new_fd = file_table_locked.dup(old_fd, new_fd, flags, max_fds)?;
 
     Ok(SyscallReturn::Return(new_fd as _))
 }
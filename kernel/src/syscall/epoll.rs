@@ -7,7 +7,7 @@ use crate::{
     events::IoEvents,
     fs::{
         epoll::{EpollCtl, EpollEvent, EpollFile, EpollFlags},
-        file_table::{get_file_fast, FdFlags, FileDesc},
+        file_table::{get_file_fast, max_fds, FdFlags, FileDesc},
         utils::CreationFlags,
     },
     prelude::*,
@@ -41,8 +41,9 @@ pub fn sys_epoll_create1(flags: u32, ctx: &Context) -> Result<SyscallReturn> {
     };
 
     let epoll_file: Arc<EpollFile> = EpollFile::new();
+    let max_fds = max_fds(&ctx.process);
     let file_table = ctx.thread_local.file_table().borrow();
-    let fd = file_table.write().insert(epoll_file, fd_flags);
+    let fd = file_table.write().insert(epoll_file, fd_flags, max_fds)?;
     Ok(SyscallReturn::Return(fd as _))
 }
 
@@ -21,7 +21,7 @@ use crate::{
     events::IoEvents,
     fs::{
         file_handle::FileLike,
-        file_table::{FdFlags, FileDesc},
+        file_table::{max_fds, FdFlags, FileDesc},
         utils::{CreationFlags, InodeMode, InodeType, Metadata, StatusFlags},
     },
     prelude::*,
@@ -35,7 +35,7 @@ use crate::{
 pub fn sys_eventfd(init_val: u64, ctx: &Context) -> Result<SyscallReturn> {
     debug!("init_val = 0x{:x}", init_val);
 
-    let fd = do_sys_eventfd2(init_val, Flags::empty(), ctx);
+    let fd = do_sys_eventfd2(init_val, Flags::empty(), ctx)?;
 
     Ok(SyscallReturn::Return(fd as _))
 }
@@ -46,14 +46,15 @@ pub fn sys_eventfd2(init_val: u64, flags: u32, ctx: &Context) -> Result<SyscallR
         .ok_or_else(|| Error::with_message(Errno::EINVAL, "unknown flags"))?;
     debug!("init_val = 0x{:x}, flags = {:?}", init_val, flags);
 
-    let fd = do_sys_eventfd2(init_val, flags, ctx);
+    let fd = do_sys_eventfd2(init_val, flags, ctx)?;
 
     Ok(SyscallReturn::Return(fd as _))
 }
 
-fn do_sys_eventfd2(init_val: u64, flags: Flags, ctx: &Context) -> FileDesc {
+fn do_sys_eventfd2(init_val: u64, flags: Flags, ctx: &Context) -> Result<FileDesc> {
     let event_file = EventFile::new(init_val, flags);
     let fd = {
+        let max_fds = max_fds(&ctx.process);
         let file_table = ctx.thread_local.file_table().borrow();
         let mut file_table_locked = file_table.write();
         let fd_flags = if flags.contains(Flags::EFD_CLOEXEC) {
@@ -61,9 +62,9 @@ fn do_sys_eventfd2(init_val: u64, flags: Flags, ctx: &Context) -> FileDesc {
         } else {
             FdFlags::empty()
         };
-        file_table_locked.insert(Arc::new(event_file), fd_flags)
+        file_table_locked.insert(Arc::new(event_file), fd_flags, max_fds)?
     };
-    fd
+    Ok(fd)
 }
 
 bitflags! {
@@ -195,6 +196,9 @@ impl FileLike for EventFile {
         }
 
         let supplied_value = reader.read_val::<u64>()?;
+        if supplied_value == u64::MAX {
+            return_errno_with_message!(Errno::EINVAL, "the value is not allowed to be written");
+        }
 
         // Try to add counter val at first
         if self.add_counter_val(supplied_value).is_ok() {
@@ -15,9 +15,14 @@ use crate::{
     },
     prelude::*,
     process::{
-        check_executable_file, load_program_to_vm, posix_thread::ThreadName, Credentials, Process,
-        MAX_ARGV_NUMBER, MAX_ARG_LEN, MAX_ENVP_NUMBER, MAX_ENV_LEN,
+        check_executable_file, load_program_to_vm,
+        personality::Personality,
+        posix_thread::{kill_other_threads, ThreadName},
+        signal::SigStack,
+        Credentials, ExecIds, Process, ProcessVm, MAX_ARGV_NUMBER, MAX_ARG_LEN, MAX_ENVP_NUMBER,
+        MAX_ENV_LEN,
     },
+    security::audit,
 };
 
 pub fn sys_execve(
@@ -66,7 +71,8 @@ fn lookup_executable_file(
         let file = get_file_fast!(&mut file_table, dfd);
         file.as_inode_or_err()?.dentry().clone()
     } else {
-        let fs_resolver = ctx.posix_thread.fs().resolver().read();
+        let fs_ref = ctx.thread_local.borrow_fs();
+        let fs_resolver = fs_ref.resolver().read();
         let fs_path = FsPath::new(dfd, &filename)?;
         if flags.contains(OpenFlags::AT_SYMLINK_NOFOLLOW) {
             fs_resolver.lookup_no_follow(&fs_path)?
@@ -94,19 +100,29 @@ fn do_execve(
         ..
     } = ctx;
 
+    // The 32-bit system calls pass the arrays of 32-bit pointers.
+    #[cfg(target_arch = "x86_64")]
+    let is_compat = user_context.is_compat_syscall();
+    #[cfg(not(target_arch = "x86_64"))]
+    let is_compat = false;
+
     let executable_path = elf_file.abs_path();
-    let argv = read_cstring_vec(argv_ptr_ptr, MAX_ARGV_NUMBER, MAX_ARG_LEN, ctx)?;
-    let envp = read_cstring_vec(envp_ptr_ptr, MAX_ENVP_NUMBER, MAX_ENV_LEN, ctx)?;
+    let argv = read_cstring_vec(argv_ptr_ptr, MAX_ARGV_NUMBER, MAX_ARG_LEN, is_compat, ctx)?;
+    let envp = read_cstring_vec(envp_ptr_ptr, MAX_ENVP_NUMBER, MAX_ENV_LEN, is_compat, ctx)?;
     debug!(
         "filename: {:?}, argv = {:?}, envp = {:?}",
         executable_path, argv, envp
     );
+    audit::log_path(&executable_path, ctx);
+    audit::log_execve(&argv, ctx);
     // FIXME: should we set thread name in execve?
     *posix_thread.thread_name().lock() =
         Some(ThreadName::new_from_executable_path(&executable_path)?);
     // clear ctid
     // FIXME: should we clear ctid when execve?
     thread_local.clear_child_tid().set(0);
+    // Like Linux, the thread and process keyrings are not inherited by the new program.
+    posix_thread.keyrings().lock().reset_on_exec();
 
     // Ensure that the file descriptors with the close-on-exec flag are closed.
     // FIXME: This is just wrong if the file table is shared with other processes.
@@ -117,27 +133,99 @@ fn do_execve(
         .close_files_on_exec();
     drop(closed_files);
 
-    debug!("load program to root vmar");
+    // Similar to Linux, the set-user-ID and set-group-ID bits are ignored for traced threads,
+    // since the tracer could otherwise control a privileged program.
+    let no_new_privs = posix_thread.no_new_privs() || posix_thread.ptrace().is_traced();
+
+    // Like Linux, the personality flags that make a program easier to exploit (e.g., disabling
+    // the address space layout randomization) are cleared if the program gains privileges.
+    let elf_mode = elf_file.mode()?;
+    let is_privileged_exec = !no_new_privs && (elf_mode.has_set_uid() || elf_mode.has_set_gid());
+    if is_privileged_exec {
+        process.set_personality(process.personality() & !Personality::CLEAR_ON_SETID.bits());
+    }
+    let personality = Personality::from_bits_truncate(process.personality());
+
+    // The new program is told about the IDs that it will run with (see `set_uid_from_elf` and
+    // `set_gid_from_elf`) before they are actually changed.
+    let exec_ids = {
+        let credentials = posix_thread.credentials();
+        ExecIds {
+            uid: credentials.ruid(),
+            euid: if !no_new_privs && elf_mode.has_set_uid() {
+                elf_file.owner()?
+            } else {
+                credentials.euid()
+            },
+            gid: credentials.rgid(),
+            egid: if !no_new_privs && elf_mode.has_set_gid() {
+                elf_file.group()?
+            } else {
+                credentials.egid()
+            },
+        }
+    };
+
+    debug!("load program to a new process vm");
+    let process_vm = ProcessVm::alloc_with_personality(personality);
     let (new_executable_path, elf_load_info) = {
-        let fs_resolver = &*posix_thread.fs().resolver().read();
-        let process_vm = process.vm();
-        load_program_to_vm(process_vm, elf_file.clone(), argv, envp, fs_resolver, 1)?
+        let fs_ref = thread_local.borrow_fs();
+        let fs_resolver = &*fs_ref.resolver().read();
+        load_program_to_vm(
+            &process_vm,
+            elf_file.clone(),
+            argv,
+            envp,
+            fs_resolver,
+            exec_ids,
+        )?
     };
 
+    // The other threads must not run the old program once its address space is replaced.
+    kill_other_threads(ctx)?;
+
+    // Like Linux, the new program runs in a new address space instead of the old one, which may
+    // be shared with other processes (e.g., the parent created this process with `vfork`).
+    let process_vm = Arc::new(process_vm);
+    ctx.task
+        .user_space()
+        .unwrap()
+        .replace_vm_space(process_vm.root_vmar().vm_space().clone());
+    thread_local.set_process_vm(process_vm.clone());
+    process.set_vm(process_vm);
+
     // After the program has been successfully loaded, the virtual memory of the current process
     // is initialized. Hence, it is necessary to clear the previously recorded robust list.
-    *thread_local.robust_list().borrow_mut() = None;
+    thread_local.robust_list().set(0);
+    // The parent suspended by `vfork` can continue as the child no longer runs the old program.
+    thread_local.wake_vfork_parent();
     debug!("load elf in execve succeeds");
 
     let credentials = posix_thread.credentials_mut();
-    set_uid_from_elf(process, &credentials, &elf_file)?;
-    set_gid_from_elf(process, &credentials, &elf_file)?;
+    set_uid_from_elf(process, &credentials, &elf_file, no_new_privs)?;
+    set_gid_from_elf(process, &credentials, &elf_file, no_new_privs)?;
+    credentials.apply_exec_capsets(is_privileged_exec);
     credentials.set_keep_capabilities(false);
+    // Like Linux, a process that gains privileges cannot be dumped, since its memory may contain
+    // sensitive data.
+    let is_dumpable = {
+        let credentials = posix_thread.credentials();
+        credentials.euid() == credentials.ruid() && credentials.egid() == credentials.rgid()
+    };
+    process.set_dumpable(is_dumpable);
+    // The parent can no longer change the process group of the process (see `setpgid`).
+    process.set_called_execve();
 
     // set executable path
     process.set_executable_path(new_executable_path);
     // set signal disposition to default
     process.sig_dispositions().lock().inherit();
+    // The POSIX timers of the old program are deleted.
+    process.timer_manager().clear_posix_timers();
+    // Like Linux, the alternate signal stack and the signal context of the old program are
+    // discarded.
+    *thread_local.sig_stack().borrow_mut() = SigStack::default();
+    thread_local.sig_context().set(None);
     // set cpu context to default
     *user_context.general_regs_mut() = RawGeneralRegs::default();
     user_context.set_tls_pointer(0);
@@ -153,6 +241,15 @@ fn do_execve(
     // set new user stack top
     user_context.set_stack_pointer(elf_load_info.user_stack_top() as _);
     debug!("user stack top: 0x{:x}", elf_load_info.user_stack_top());
+    // The 32-bit programs run in the compatibility mode. Like Linux, the thread-local storage
+    // segments of the old program are discarded.
+    #[cfg(target_arch = "x86_64")]
+    {
+        user_context.set_compat_mode(elf_load_info.is_compat());
+        user_context.clear_tls_segments();
+    }
+
+    posix_thread.ptrace().stop_at_exec(ctx, user_context);
     Ok(())
 }
 
@@ -174,6 +271,7 @@ fn read_cstring_vec(
     array_ptr: Vaddr,
     max_string_number: usize,
     max_string_len: usize,
+    is_compat: bool,
     ctx: &Context,
 ) -> Result<Vec<CString>> {
     let mut res = Vec::new();
@@ -185,8 +283,15 @@ fn read_cstring_vec(
     let mut find_null = false;
     let user_space = ctx.user_space();
     for _ in 0..max_string_number {
-        let cstring_ptr = user_space.read_val::<usize>(read_addr)?;
-        read_addr += 8;
+        let cstring_ptr = if is_compat {
+            let cstring_ptr = user_space.read_val::<u32>(read_addr)? as usize;
+            read_addr += size_of::<u32>();
+            cstring_ptr
+        } else {
+            let cstring_ptr = user_space.read_val::<usize>(read_addr)?;
+            read_addr += size_of::<usize>();
+            cstring_ptr
+        };
         // read a null pointer
         if cstring_ptr == 0 {
             find_null = true;
@@ -202,12 +307,15 @@ fn read_cstring_vec(
 }
 
 /// Sets uid for credentials as the same of uid of elf file if elf file has `set_uid` bit.
+///
+/// The `set_uid` bit is ignored if `no_new_privs` is set.
 fn set_uid_from_elf(
     current: &Process,
     credentials: &Credentials<WriteOp>,
     elf_file: &Dentry,
+    no_new_privs: bool,
 ) -> Result<()> {
-    if elf_file.mode()?.has_set_uid() {
+    if !no_new_privs && elf_file.mode()?.has_set_uid() {
         let uid = elf_file.owner()?;
         credentials.set_euid(uid);
 
@@ -220,12 +328,15 @@ fn set_uid_from_elf(
 }
 
 /// Sets gid for credentials as the same of gid of elf file if elf file has `set_gid` bit.
+///
+/// The `set_gid` bit is ignored if `no_new_privs` is set.
 fn set_gid_from_elf(
     current: &Process,
     credentials: &Credentials<WriteOp>,
     elf_file: &Dentry,
+    no_new_privs: bool,
 ) -> Result<()> {
-    if elf_file.mode()?.has_set_gid() {
+    if !no_new_privs && elf_file.mode()?.has_set_gid() {
         let gid = elf_file.group()?;
         credentials.set_egid(gid);
 
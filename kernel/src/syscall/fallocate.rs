@@ -7,7 +7,7 @@ use crate::{
         utils::FallocMode,
     },
     prelude::*,
-    process::ResourceType,
+    process::rlimit::check_file_size,
 };
 
 pub fn sys_fallocate(
@@ -22,7 +22,7 @@ pub fn sys_fallocate(
         fd, mode, offset, len
     );
 
-    check_offset_and_len(offset, len, ctx)?;
+    check_offset_and_len(offset, len)?;
 
     let mut file_table = ctx.thread_local.file_table().borrow_mut();
     let file = get_file_fast!(&mut file_table, fd);
@@ -36,7 +36,7 @@ pub fn sys_fallocate(
     Ok(SyscallReturn::Return(0))
 }
 
-fn check_offset_and_len(offset: i64, len: i64, ctx: &Context) -> Result<()> {
+fn check_offset_and_len(offset: i64, len: i64) -> Result<()> {
     if offset < 0 || len <= 0 {
         return_errno_with_message!(
             Errno::EINVAL,
@@ -47,16 +47,7 @@ fn check_offset_and_len(offset: i64, len: i64, ctx: &Context) -> Result<()> {
         return_errno_with_message!(Errno::EINVAL, "offset+len has overflowed");
     }
 
-    let max_file_size = {
-        let resource_limits = ctx.process.resource_limits().lock();
-        resource_limits
-            .get_rlimit(ResourceType::RLIMIT_FSIZE)
-            .get_cur() as usize
-    };
-    if (offset + len) as usize > max_file_size {
-        return_errno_with_message!(Errno::EFBIG, "offset+len exceeds the maximum file size");
-    }
-    Ok(())
+    check_file_size((offset + len) as usize)
 }
 
 bitflags! {
@@ -4,13 +4,14 @@ use super::SyscallReturn;
 use crate::{
     fs::{
         file_handle::FileLike,
-        file_table::{get_file_fast, FdFlags, FileDesc, WithFileTable},
+        file_table::{get_file_fast, max_fds, FdFlags, FileDesc, WithFileTable},
+        pipe::{PipeReader, PipeWriter, PIPE_MAX_SIZE},
         utils::{
             FileRange, RangeLockItem, RangeLockItemBuilder, RangeLockType, StatusFlags, OFFSET_MAX,
         },
     },
     prelude::*,
-    process::{process_table, Pid},
+    process::{credentials::capabilities::CapSet, process_table, Pid},
 };
 
 pub fn sys_fcntl(fd: FileDesc, cmd: i32, arg: u64, ctx: &Context) -> Result<SyscallReturn> {
@@ -31,12 +32,21 @@ pub fn sys_fcntl(fd: FileDesc, cmd: i32, arg: u64, ctx: &Context) -> Result<Sysc
         }),
         FcntlCmd::F_GETOWN => handle_getown(fd, ctx),
         FcntlCmd::F_SETOWN => handle_setown(fd, arg, ctx),
+        FcntlCmd::F_SETPIPE_SZ => handle_setpipe_sz(fd, arg, ctx),
+        FcntlCmd::F_GETPIPE_SZ => handle_getpipe_sz(fd, ctx),
     }
 }
 
 fn handle_dupfd(fd: FileDesc, arg: u64, flags: FdFlags, ctx: &Context) -> Result<SyscallReturn> {
+    let max_fds = max_fds(&ctx.process);
+    if arg >= max_fds as u64 {
+        return_errno_with_message!(Errno::EINVAL, "the file descriptor exceeds the limit");
+    }
+
     let file_table = ctx.thread_local.file_table().borrow();
-    let new_fd = file_table.write().dup(fd, arg as FileDesc, flags)?;
+    let new_fd = file_table
+        .write()
+        .dup(fd, arg as FileDesc, flags, max_fds)?;
     Ok(SyscallReturn::Return(new_fd as _))
 }
 
@@ -129,7 +139,11 @@ fn handle_setlk(
 fn handle_getown(fd: FileDesc, ctx: &Context) -> Result<SyscallReturn> {
     let mut file_table = ctx.thread_local.file_table().borrow_mut();
     file_table.read_with(|inner| {
-        let pid = inner.get_entry(fd)?.owner().unwrap_or(0);
+        let pid = inner
+            .get_entry(fd)?
+            .owner()
+            .and_then(|pid| ctx.process.pid_ns().local_id(pid))
+            .unwrap_or(0);
         Ok(SyscallReturn::Return(pid as _))
     })
 }
@@ -146,7 +160,12 @@ fn handle_setown(fd: FileDesc, arg: u64, ctx: &Context) -> Result<SyscallReturn>
     let owner_process = if pid == 0 {
         None
     } else {
-        Some(process_table::get_process(pid).ok_or(Error::with_message(
+        let process = ctx
+            .process
+            .pid_ns()
+            .global_id(pid)
+            .and_then(process_table::get_process);
+        Some(process.ok_or(Error::with_message(
             Errno::ESRCH,
             "cannot set_owner with an invalid pid",
         ))?)
@@ -159,6 +178,50 @@ fn handle_setown(fd: FileDesc, arg: u64, ctx: &Context) -> Result<SyscallReturn>
     Ok(SyscallReturn::Return(0))
 }
 
+fn handle_setpipe_sz(fd: FileDesc, arg: u64, ctx: &Context) -> Result<SyscallReturn> {
+    // The size is rounded up to a power of two, and is at least one page.
+    let Some(new_size) = (arg as u32 as usize)
+        .max(PAGE_SIZE)
+        .checked_next_power_of_two()
+        .filter(|size| *size <= i32::MAX as usize)
+    else {
+        return_errno_with_message!(Errno::EINVAL, "the pipe size is too large");
+    };
+    if new_size > PIPE_MAX_SIZE
+        && !ctx
+            .posix_thread
+            .credentials()
+            .effective_capset()
+            .contains(CapSet::SYS_RESOURCE)
+    {
+        return_errno_with_message!(Errno::EPERM, "the pipe size exceeds the limit");
+    }
+
+    let mut file_table = ctx.thread_local.file_table().borrow_mut();
+    let file = get_file_fast!(&mut file_table, fd);
+    if let Some(pipe_reader) = file.downcast_ref::<PipeReader>() {
+        pipe_reader.set_capacity(new_size)?;
+    } else if let Some(pipe_writer) = file.downcast_ref::<PipeWriter>() {
+        pipe_writer.set_capacity(new_size)?;
+    } else {
+        return_errno_with_message!(Errno::EBADF, "the file is not a pipe");
+    }
+    Ok(SyscallReturn::Return(new_size as _))
+}
+
+fn handle_getpipe_sz(fd: FileDesc, ctx: &Context) -> Result<SyscallReturn> {
+    let mut file_table = ctx.thread_local.file_table().borrow_mut();
+    let file = get_file_fast!(&mut file_table, fd);
+    let capacity = if let Some(pipe_reader) = file.downcast_ref::<PipeReader>() {
+        pipe_reader.capacity()
+    } else if let Some(pipe_writer) = file.downcast_ref::<PipeWriter>() {
+        pipe_writer.capacity()
+    } else {
+        return_errno_with_message!(Errno::EBADF, "the file is not a pipe");
+    };
+    Ok(SyscallReturn::Return(capacity as _))
+}
+
 #[repr(i32)]
 #[derive(Debug, Clone, Copy, TryFromInt)]
 #[allow(non_camel_case_types)]
@@ -174,6 +237,8 @@ enum FcntlCmd {
     F_SETOWN = 8,
     F_GETOWN = 9,
     F_DUPFD_CLOEXEC = 1030,
+    F_SETPIPE_SZ = 1031,
+    F_GETPIPE_SZ = 1032,
 }
 
 #[allow(non_camel_case_types)]
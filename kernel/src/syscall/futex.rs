@@ -6,8 +6,8 @@ use crate::{
     current_userspace,
     prelude::*,
     process::posix_thread::futex::{
-        futex_op_and_flags_from_u32, futex_requeue, futex_wait, futex_wait_bitset, futex_wake,
-        futex_wake_bitset, FutexFlags, FutexOp,
+        futex_lock_pi, futex_op_and_flags_from_u32, futex_requeue, futex_unlock_pi, futex_wait,
+        futex_wait_bitset, futex_wake, futex_wake_bitset, FutexFlags, FutexOp,
     },
     syscall::SyscallReturn,
     time::{
@@ -55,6 +55,8 @@ pub fn sys_futex(
             // Ref: <https://github.com/torvalds/linux/commit/4fbf5d6837bf81fd7a27d771358f4ee6c4f243f8>
             return_errno_with_message!(Errno::ENOSYS, "FUTEX_WAIT cannot use CLOCK_REALTIME");
         }
+        // From man(2) futex: FUTEX_LOCK_PI always measures the timeout against CLOCK_REALTIME.
+        let is_real_time = is_real_time || futex_op == FutexOp::FUTEX_LOCK_PI;
 
         let timeout = {
             // From man(2) futex:
@@ -119,18 +121,33 @@ pub fn sys_futex(
             futex_wake_bitset(futex_addr as _, max_count, bitset as _, pid)
                 .map(|count| count as isize)
         }
-        FutexOp::FUTEX_REQUEUE => {
+        FutexOp::FUTEX_REQUEUE | FutexOp::FUTEX_CMP_REQUEUE => {
             let max_nwakes = get_futex_val(futex_val as i32)?;
             let max_nrequeues = get_futex_val(utime_addr as i32)?;
+            let cmp_val = if futex_op == FutexOp::FUTEX_CMP_REQUEUE {
+                Some(bitset as i32)
+            } else {
+                None
+            };
             futex_requeue(
                 futex_addr as _,
                 max_nwakes,
                 max_nrequeues,
                 futex_new_addr as _,
+                cmp_val,
+                ctx,
                 pid,
             )
-            .map(|nwakes| nwakes as _)
+            .map(|count| count as _)
+        }
+        FutexOp::FUTEX_LOCK_PI => {
+            let timeout = get_futex_timeout(utime_addr)?;
+            futex_lock_pi(futex_addr as _, timeout, false, ctx, pid).map(|_| 0)
+        }
+        FutexOp::FUTEX_TRYLOCK_PI => {
+            futex_lock_pi(futex_addr as _, None, true, ctx, pid).map(|_| 0)
         }
+        FutexOp::FUTEX_UNLOCK_PI => futex_unlock_pi(futex_addr as _, ctx, pid).map(|_| 0),
         _ => {
             warn!("futex op = {:?}", futex_op);
             return_errno_with_message!(Errno::EINVAL, "unsupported futex op");
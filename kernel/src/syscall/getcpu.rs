@@ -0,0 +1,25 @@
+// SPDX-License-Identifier: MPL-2.0
+
+use ostd::{cpu::PinCurrentCpu, task::disable_preempt};
+
+use super::SyscallReturn;
+use crate::prelude::*;
+
+pub fn sys_getcpu(cpu: Vaddr, node: Vaddr, _tcache: Vaddr, ctx: &Context) -> Result<SyscallReturn> {
+    let current_cpu = disable_preempt().current_cpu().as_usize() as u32;
+    // TODO: Support NUMA nodes.
+    let current_node = 0u32;
+    debug!(
+        "[sys_getcpu]: cpu = {}, node = {}",
+        current_cpu, current_node
+    );
+
+    if cpu != 0 {
+        ctx.user_space().write_val(cpu, &current_cpu)?;
+    }
+    if node != 0 {
+        ctx.user_space().write_val(node, &current_node)?;
+    }
+
+    Ok(SyscallReturn::Return(0))
+}
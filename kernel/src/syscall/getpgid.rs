@@ -13,12 +13,17 @@ pub fn sys_getpgid(pid: Pid, ctx: &Context) -> Result<SyscallReturn> {
     //     return_errno_with_message!(Errno::EINVAL, "pid cannot be negative");
     // }
 
+    let pid_ns = ctx.process.pid_ns();
+
     // if pid is 0, should return the pgid of current process
     if pid == 0 {
-        return Ok(SyscallReturn::Return(ctx.process.pgid() as _));
+        let pgid = pid_ns.local_id(ctx.process.pgid()).unwrap_or(0);
+        return Ok(SyscallReturn::Return(pgid as _));
     }
 
-    let process = process_table::get_process(pid)
+    let process = pid_ns
+        .global_id(pid)
+        .and_then(process_table::get_process)
         .ok_or(Error::with_message(Errno::ESRCH, "process does not exist"))?;
 
     if !Arc::ptr_eq(&ctx.process.session().unwrap(), &process.session().unwrap()) {
@@ -28,5 +33,6 @@ pub fn sys_getpgid(pid: Pid, ctx: &Context) -> Result<SyscallReturn> {
         );
     }
 
-    Ok(SyscallReturn::Return(process.pgid() as _))
+    let pgid = pid_ns.local_id(process.pgid()).unwrap_or(0);
+    Ok(SyscallReturn::Return(pgid as _))
 }
@@ -1,25 +1,37 @@
 // SPDX-License-Identifier: MPL-2.0
 
 use super::SyscallReturn;
-use crate::{device, prelude::*};
+use crate::{prelude::*, util::random};
 
 pub fn sys_getrandom(buf: Vaddr, count: usize, flags: u32, ctx: &Context) -> Result<SyscallReturn> {
-    let flags = GetRandomFlags::from_bits_truncate(flags);
+    let flags = GetRandomFlags::from_bits(flags)
+        .ok_or_else(|| Error::with_message(Errno::EINVAL, "the flags are invalid"))?;
     debug!(
         "buf = 0x{:x}, count = 0x{:x}, flags = {:?}",
         buf, count, flags
     );
-    // TODO: support nonblock flag.
-    // Currently our getrandom implementation relies on x86-specific `rdrand` instruction, so it will never block.
-    let mut buffer = vec![0u8; count];
-    let read_len = if flags.contains(GetRandomFlags::GRND_RANDOM) {
-        device::Random::getrandom(&mut buffer)?
-    } else {
-        device::Urandom::getrandom(&mut buffer)?
-    };
-    ctx.user_space()
-        .write_bytes(buf, &mut VmReader::from(buffer.as_slice()))?;
-    Ok(SyscallReturn::Return(read_len as isize))
+
+    if flags.contains(GetRandomFlags::GRND_INSECURE | GetRandomFlags::GRND_RANDOM) {
+        return_errno_with_message!(
+            Errno::EINVAL,
+            "GRND_INSECURE cannot be used with GRND_RANDOM"
+        );
+    }
+
+    // Like Linux, `GRND_RANDOM` makes no difference, since `/dev/random` and `/dev/urandom`
+    // produce the same bytes once the CRNG is ready.
+    if !flags.contains(GetRandomFlags::GRND_INSECURE) && !random::is_ready() {
+        if flags.contains(GetRandomFlags::GRND_NONBLOCK) {
+            return_errno_with_message!(Errno::EAGAIN, "the CRNG is not ready");
+        }
+        random::wait_until_ready()?;
+    }
+
+    // Like Linux, the count is limited so that the return value does not overflow.
+    let count = count.min(i32::MAX as usize & !(PAGE_SIZE - 1));
+    let mut writer = ctx.user_space().writer(buf, count)?;
+    let written = random::getrandom_user(&mut writer)?;
+    Ok(SyscallReturn::Return(written as isize))
 }
 
 bitflags::bitflags! {
@@ -0,0 +1,138 @@
+// SPDX-License-Identifier: MPL-2.0
+
+use super::SyscallReturn;
+use crate::{
+    fs::{
+        file_table::{get_file_fast, max_fds, FdFlags, FileDesc},
+        io_uring::{
+            uapi::{
+                IoUringEnterFlags, IoUringFeatures, IoUringParams, IoUringSetupFlags,
+                IORING_MAX_CQ_ENTRIES, IORING_MAX_ENTRIES,
+            },
+            IoUring,
+        },
+    },
+    prelude::*,
+};
+
+pub fn sys_io_uring_setup(entries: u32, params_ptr: Vaddr, ctx: &Context) -> Result<SyscallReturn> {
+    let user_space = ctx.user_space();
+    let mut params: IoUringParams = user_space.read_val(params_ptr)?;
+    debug!("entries = {}, params = {:?}", entries, params);
+
+    if params.resv.iter().any(|resv| *resv != 0) {
+        return_errno_with_message!(Errno::EINVAL, "the reserved fields are not zero");
+    }
+    let flags = IoUringSetupFlags::from_bits(params.flags)
+        .ok_or_else(|| Error::with_message(Errno::EINVAL, "invalid setup flags"))?;
+    if flags.intersects(
+        IoUringSetupFlags::IORING_SETUP_IOPOLL
+            | IoUringSetupFlags::IORING_SETUP_SQPOLL
+            | IoUringSetupFlags::IORING_SETUP_SQ_AFF,
+    ) {
+        return_errno_with_message!(Errno::EINVAL, "the setup flags are not supported");
+    }
+
+    let is_clamped = flags.contains(IoUringSetupFlags::IORING_SETUP_CLAMP);
+
+    if entries == 0 {
+        return_errno_with_message!(Errno::EINVAL, "the number of entries is zero");
+    }
+    let sq_entries = if entries <= IORING_MAX_ENTRIES {
+        entries.next_power_of_two()
+    } else if is_clamped {
+        IORING_MAX_ENTRIES
+    } else {
+        return_errno_with_message!(Errno::EINVAL, "the number of entries is too large");
+    };
+
+    let cq_entries = if flags.contains(IoUringSetupFlags::IORING_SETUP_CQSIZE) {
+        let cq_entries = params.cq_entries;
+        if cq_entries == 0 {
+            return_errno_with_message!(Errno::EINVAL, "the number of CQ entries is zero");
+        }
+        let cq_entries = if cq_entries <= IORING_MAX_CQ_ENTRIES {
+            cq_entries.next_power_of_two()
+        } else if is_clamped {
+            IORING_MAX_CQ_ENTRIES
+        } else {
+            return_errno_with_message!(Errno::EINVAL, "the number of CQ entries is too large");
+        };
+        if cq_entries < sq_entries {
+            return_errno_with_message!(
+                Errno::EINVAL,
+                "the number of CQ entries is less than that of SQ entries"
+            );
+        }
+        cq_entries
+    } else {
+        sq_entries * 2
+    };
+
+    let io_uring = IoUring::new(sq_entries, cq_entries)?;
+
+    params.sq_entries = sq_entries;
+    params.cq_entries = cq_entries;
+    params.features = (IoUringFeatures::IORING_FEAT_SINGLE_MMAP
+        | IoUringFeatures::IORING_FEAT_NODROP
+        | IoUringFeatures::IORING_FEAT_SUBMIT_STABLE)
+        .bits();
+    io_uring.fill_offsets(&mut params);
+    user_space.write_val(params_ptr, &params)?;
+
+    let fd = {
+        let max_fds = max_fds(&ctx.process);
+        let file_table = ctx.thread_local.file_table().borrow();
+        let mut file_table_locked = file_table.write();
+        file_table_locked.insert(io_uring, FdFlags::CLOEXEC, max_fds)?
+    };
+
+    Ok(SyscallReturn::Return(fd as _))
+}
+
+pub fn sys_io_uring_enter(
+    fd: FileDesc,
+    to_submit: u32,
+    min_complete: u32,
+    flags: u32,
+    sig: Vaddr,
+    sigsz: usize,
+    ctx: &Context,
+) -> Result<SyscallReturn> {
+    let flags = IoUringEnterFlags::from_bits(flags)
+        .ok_or_else(|| Error::with_message(Errno::EINVAL, "invalid enter flags"))?;
+    debug!(
+        "fd = {}, to_submit = {}, min_complete = {}, flags = {:?}, sig = 0x{:x}, sigsz = {}",
+        fd, to_submit, min_complete, flags, sig, sigsz
+    );
+
+    if flags.contains(IoUringEnterFlags::IORING_ENTER_EXT_ARG) {
+        return_errno_with_message!(Errno::EINVAL, "the extended arguments are not supported");
+    }
+    if sig != 0 {
+        warn!("the signal mask of io_uring_enter is not supported");
+    }
+
+    let mut file_table = ctx.thread_local.file_table().borrow_mut();
+    let file = get_file_fast!(&mut file_table, fd).into_owned();
+    drop(file_table);
+    let io_uring = file
+        .downcast_ref::<IoUring>()
+        .ok_or_else(|| Error::with_message(Errno::EOPNOTSUPP, "the file is not an io_uring"))?;
+
+    let num_submitted = io_uring.submit(to_submit, ctx)?;
+
+    let min_complete = if flags.contains(IoUringEnterFlags::IORING_ENTER_GETEVENTS) {
+        min_complete
+    } else {
+        0
+    };
+    match io_uring.wait_completions(min_complete, ctx) {
+        Ok(()) => (),
+        // The submission is not undone if the wait is interrupted.
+        Err(_) if num_submitted > 0 => (),
+        Err(err) => return Err(err),
+    }
+
+    Ok(SyscallReturn::Return(num_submitted as _))
+}
@@ -0,0 +1,309 @@
+// SPDX-License-Identifier: MPL-2.0
+
+use core::time::Duration;
+
+use super::SyscallReturn;
+use crate::{
+    prelude::*,
+    process::{Gid, Uid},
+    security::keys::{
+        join_session_keyring, lookup_key, search_thread_keyrings, Key, KeyPerm, KeyPerms,
+        KeySerial, KeyType, LookupFlags, MAX_DESC_LEN,
+    },
+};
+
+pub fn sys_add_key(
+    type_addr: Vaddr,
+    description_addr: Vaddr,
+    payload_addr: Vaddr,
+    payload_len: usize,
+    keyring: KeySerial,
+    ctx: &Context,
+) -> Result<SyscallReturn> {
+    let type_ = read_key_type(type_addr, ctx)?;
+    let description = read_description(description_addr, ctx)?;
+    debug!(
+        "type = {:?}, description = {:?}, payload_len = {}, keyring = {}",
+        type_, description, payload_len, keyring
+    );
+
+    if type_ == KeyType::Keyring && description.starts_with('.') {
+        return_errno_with_message!(Errno::EPERM, "the keyring name is reserved");
+    }
+    let payload = read_payload(payload_addr, payload_len, ctx)?;
+
+    let posix_thread = ctx.posix_thread;
+    let credentials = posix_thread.credentials();
+    let keyring = lookup_key(keyring, LookupFlags::CREATE, posix_thread)?;
+    keyring.check_perm(&credentials, KeyPerm::WRITE)?;
+
+    // Like Linux, a key of the same type and description in the keyring is updated instead of
+    // being replaced, if the type supports updating.
+    if type_ != KeyType::Keyring
+        && let Some(key) = keyring.key().links()?.into_iter().find(|key| {
+            key.type_() == type_ && key.description() == description && key.check_valid().is_ok()
+        })
+    {
+        key.check_perm(&credentials, keyring.is_possessed(), KeyPerm::WRITE)?;
+        key.update(payload)?;
+        return Ok(SyscallReturn::Return(key.serial() as _));
+    }
+
+    let key = Key::new(
+        type_,
+        description,
+        payload,
+        credentials.fsuid(),
+        credentials.fsgid(),
+        type_.default_perm(),
+    )?;
+    keyring.key().link(&key)?;
+
+    Ok(SyscallReturn::Return(key.serial() as _))
+}
+
+pub fn sys_request_key(
+    type_addr: Vaddr,
+    description_addr: Vaddr,
+    callout_info_addr: Vaddr,
+    dest_keyring: KeySerial,
+    ctx: &Context,
+) -> Result<SyscallReturn> {
+    let type_ = read_key_type(type_addr, ctx)?;
+    let description = read_description(description_addr, ctx)?;
+    debug!(
+        "type = {:?}, description = {:?}, callout_info_addr = {:#x}, dest_keyring = {}",
+        type_, description, callout_info_addr, dest_keyring
+    );
+
+    let posix_thread = ctx.posix_thread;
+    let dest_keyring = if dest_keyring != 0 {
+        let keyring = lookup_key(dest_keyring, LookupFlags::CREATE, posix_thread)?;
+        keyring.check_perm(&posix_thread.credentials(), KeyPerm::WRITE)?;
+        Some(keyring)
+    } else {
+        None
+    };
+
+    // Keys cannot be constructed by the user space, so the callout information is ignored and
+    // this fails with `ENOKEY` if the key does not exist.
+    let key = search_thread_keyrings(type_, &description, posix_thread)?;
+    if let Some(dest_keyring) = dest_keyring {
+        dest_keyring.key().link(&key)?;
+    }
+
+    Ok(SyscallReturn::Return(key.serial() as _))
+}
+
+pub fn sys_keyctl(
+    cmd: i32,
+    arg2: u64,
+    arg3: u64,
+    arg4: u64,
+    arg5: u64,
+    ctx: &Context,
+) -> Result<SyscallReturn> {
+    let cmd = KeyctlCmd::try_from(cmd)
+        .map_err(|_| Error::with_message(Errno::EOPNOTSUPP, "the keyctl command is unknown"))?;
+    debug!(
+        "cmd = {:?}, arg2 = {:#x}, arg3 = {:#x}, arg4 = {:#x}, arg5 = {:#x}",
+        cmd, arg2, arg3, arg4, arg5
+    );
+
+    let posix_thread = ctx.posix_thread;
+    let credentials = posix_thread.credentials();
+
+    let res: isize = match cmd {
+        KeyctlCmd::GetKeyringId => {
+            let flags = if arg3 != 0 {
+                LookupFlags::CREATE
+            } else {
+                LookupFlags::empty()
+            };
+            let key = lookup_key(arg2 as KeySerial, flags, posix_thread)?;
+            key.check_perm(&credentials, KeyPerm::SEARCH)?;
+            key.key().serial() as isize
+        }
+        KeyctlCmd::JoinSessionKeyring => {
+            let name = if arg2 != 0 {
+                Some(read_description(arg2 as Vaddr, ctx)?)
+            } else {
+                None
+            };
+            join_session_keyring(name, posix_thread)? as isize
+        }
+        KeyctlCmd::Update => {
+            let payload = read_payload(arg3 as Vaddr, arg4 as usize, ctx)?;
+            let key = lookup_key(arg2 as KeySerial, LookupFlags::empty(), posix_thread)?;
+            key.check_perm(&credentials, KeyPerm::WRITE)?;
+            key.key().update(payload)?;
+            0
+        }
+        KeyctlCmd::Revoke => {
+            let key = lookup_key(arg2 as KeySerial, LookupFlags::empty(), posix_thread)?;
+            // Like Linux, either the write or the setattr permission allows revoking a key.
+            if key.check_perm(&credentials, KeyPerm::WRITE).is_err() {
+                key.check_perm(&credentials, KeyPerm::SETATTR)?;
+            }
+            key.key().revoke();
+            0
+        }
+        KeyctlCmd::Chown => {
+            let uid = (arg3 as u32 != u32::MAX).then(|| Uid::new(arg3 as u32));
+            let gid = (arg4 as u32 != u32::MAX).then(|| Gid::new(arg4 as u32));
+            let key = lookup_key(arg2 as KeySerial, LookupFlags::CREATE, posix_thread)?;
+            key.check_perm(&credentials, KeyPerm::SETATTR)?;
+            key.key().chown(uid, gid, &credentials)?;
+            0
+        }
+        KeyctlCmd::SetPerm => {
+            let perms = KeyPerms::from_bits(arg3 as u32)?;
+            let key = lookup_key(arg2 as KeySerial, LookupFlags::CREATE, posix_thread)?;
+            key.check_perm(&credentials, KeyPerm::SETATTR)?;
+            key.key().set_perms(perms, &credentials)?;
+            0
+        }
+        KeyctlCmd::Describe => {
+            let key = lookup_key(arg2 as KeySerial, LookupFlags::empty(), posix_thread)?;
+            key.check_perm(&credentials, KeyPerm::VIEW)?;
+            let mut description = key.key().describe().into_bytes();
+            description.push(0);
+            // Like Linux, the description is not truncated if the buffer is too small.
+            if arg3 != 0 && arg4 as usize >= description.len() {
+                ctx.user_space()
+                    .write_bytes(arg3 as Vaddr, &mut VmReader::from(description.as_slice()))?;
+            }
+            description.len() as isize
+        }
+        KeyctlCmd::Clear => {
+            let keyring = lookup_key(arg2 as KeySerial, LookupFlags::CREATE, posix_thread)?;
+            keyring.check_perm(&credentials, KeyPerm::WRITE)?;
+            keyring.key().clear()?;
+            0
+        }
+        KeyctlCmd::Link => {
+            let key = lookup_key(arg2 as KeySerial, LookupFlags::CREATE, posix_thread)?;
+            let keyring = lookup_key(arg3 as KeySerial, LookupFlags::CREATE, posix_thread)?;
+            keyring.check_perm(&credentials, KeyPerm::WRITE)?;
+            key.check_perm(&credentials, KeyPerm::LINK)?;
+            keyring.key().link(key.key())?;
+            0
+        }
+        KeyctlCmd::Unlink => {
+            let key = lookup_key(arg2 as KeySerial, LookupFlags::FOR_UNLINK, posix_thread)?;
+            let keyring = lookup_key(arg3 as KeySerial, LookupFlags::empty(), posix_thread)?;
+            keyring.check_perm(&credentials, KeyPerm::WRITE)?;
+            keyring.key().unlink(key.key())?;
+            0
+        }
+        KeyctlCmd::Search => {
+            let keyring = lookup_key(arg2 as KeySerial, LookupFlags::empty(), posix_thread)?;
+            let type_ = read_key_type(arg3 as Vaddr, ctx)?;
+            let description = read_description(arg4 as Vaddr, ctx)?;
+            let dest_keyring = if arg5 as KeySerial != 0 {
+                let dest_keyring =
+                    lookup_key(arg5 as KeySerial, LookupFlags::CREATE, posix_thread)?;
+                dest_keyring.check_perm(&credentials, KeyPerm::WRITE)?;
+                Some(dest_keyring)
+            } else {
+                None
+            };
+
+            keyring.check_perm(&credentials, KeyPerm::SEARCH)?;
+            let key =
+                keyring
+                    .key()
+                    .search(type_, &description, &credentials, keyring.is_possessed())?;
+            if let Some(dest_keyring) = dest_keyring {
+                key.check_perm(&credentials, keyring.is_possessed(), KeyPerm::LINK)?;
+                dest_keyring.key().link(&key)?;
+            }
+            key.serial() as isize
+        }
+        KeyctlCmd::Read => {
+            let key = lookup_key(arg2 as KeySerial, LookupFlags::empty(), posix_thread)?;
+            // Like Linux, a possessed key can be read if it can be found by searching the
+            // keyrings of the thread, even without the read permission.
+            if !key.is_possessed() {
+                key.check_perm(&credentials, KeyPerm::READ)?;
+            }
+            let payload = key.key().read()?;
+            // Like Linux, the payload is not truncated if the buffer is too small.
+            if arg3 != 0 && arg4 as usize >= payload.len() {
+                ctx.user_space()
+                    .write_bytes(arg3 as Vaddr, &mut VmReader::from(payload.as_slice()))?;
+            }
+            payload.len() as isize
+        }
+        KeyctlCmd::SetTimeout => {
+            let timeout = (arg3 as u32 != 0).then(|| Duration::from_secs(arg3 as u32 as u64));
+            let key = lookup_key(arg2 as KeySerial, LookupFlags::CREATE, posix_thread)?;
+            key.check_perm(&credentials, KeyPerm::SETATTR)?;
+            key.key().set_timeout(timeout)?;
+            0
+        }
+        KeyctlCmd::Invalidate => {
+            let key = lookup_key(arg2 as KeySerial, LookupFlags::empty(), posix_thread)?;
+            key.check_perm(&credentials, KeyPerm::SEARCH)?;
+            key.key().invalidate();
+            0
+        }
+    };
+
+    Ok(SyscallReturn::Return(res as _))
+}
+
+#[repr(i32)]
+#[derive(Debug, Clone, Copy, TryFromInt)]
+enum KeyctlCmd {
+    GetKeyringId = 0,
+    JoinSessionKeyring = 1,
+    Update = 2,
+    Revoke = 3,
+    Chown = 4,
+    SetPerm = 5,
+    Describe = 6,
+    Clear = 7,
+    Link = 8,
+    Unlink = 9,
+    Search = 10,
+    Read = 11,
+    SetTimeout = 15,
+    Invalidate = 21,
+}
+
+/// The maximum size of a payload that is read from the user space.
+const MAX_PAYLOAD_LEN: usize = 1024 * 1024 - 1;
+
+/// The maximum length of the name of a key type, including the final `\0` byte.
+const MAX_TYPE_LEN: usize = 32;
+
+fn read_key_type(addr: Vaddr, ctx: &Context) -> Result<KeyType> {
+    let name = ctx.user_space().read_cstring(addr, MAX_TYPE_LEN)?;
+    let name = name
+        .to_str()
+        .map_err(|_| Error::with_message(Errno::EINVAL, "the key type is invalid"))?;
+    KeyType::from_name(name)
+}
+
+fn read_description(addr: Vaddr, ctx: &Context) -> Result<String> {
+    let description = ctx.user_space().read_cstring(addr, MAX_DESC_LEN + 1)?;
+    description
+        .into_string()
+        .map_err(|_| Error::with_message(Errno::EINVAL, "the description is not valid UTF-8"))
+}
+
+fn read_payload(addr: Vaddr, len: usize, ctx: &Context) -> Result<Vec<u8>> {
+    // Like Linux, the size is checked before the payload is read to avoid allocating a huge
+    // buffer.
+    if len > MAX_PAYLOAD_LEN {
+        return_errno_with_message!(Errno::EINVAL, "the payload is too large");
+    }
+
+    let mut payload = vec![0; len];
+    if len > 0 {
+        ctx.user_space()
+            .read_bytes(addr, &mut VmWriter::from(payload.as_mut_slice()))?;
+    }
+    Ok(payload)
+}
@@ -0,0 +1,228 @@
+// SPDX-License-Identifier: MPL-2.0
+
+use super::SyscallReturn;
+use crate::{
+    fs::{
+        file_handle::FileLike,
+        file_table::{get_file_fast, max_fds, FdFlags, FileDesc},
+    },
+    prelude::*,
+    process::credentials::capabilities::CapSet,
+    security::landlock::{AccessFs, AccessNet, Domain, Ruleset, RulesetFile, ABI_VERSION},
+};
+
+pub fn sys_landlock_create_ruleset(
+    attr_addr: Vaddr,
+    size: usize,
+    flags: u32,
+    ctx: &Context,
+) -> Result<SyscallReturn> {
+    debug!(
+        "attr_addr = {:#x}, size = {}, flags = {:#x}",
+        attr_addr, size, flags
+    );
+
+    match flags {
+        0 => (),
+        LANDLOCK_CREATE_RULESET_VERSION => {
+            if attr_addr != 0 || size != 0 {
+                return_errno_with_message!(
+                    Errno::EINVAL,
+                    "the attributes must be empty when querying the ABI version"
+                );
+            }
+            return Ok(SyscallReturn::Return(ABI_VERSION as _));
+        }
+        _ => return_errno_with_message!(Errno::EINVAL, "unknown flags"),
+    }
+
+    let attr = read_ruleset_attr(attr_addr, size, ctx)?;
+    let handled_fs = AccessFs::from_bits(attr.handled_access_fs)
+        .ok_or_else(|| Error::with_message(Errno::EINVAL, "unknown file system access rights"))?;
+    let handled_net = AccessNet::from_bits(attr.handled_access_net)
+        .ok_or_else(|| Error::with_message(Errno::EINVAL, "unknown network access rights"))?;
+    if handled_fs.is_empty() && handled_net.is_empty() {
+        return_errno_with_message!(Errno::ENOMSG, "the ruleset handles no access rights");
+    }
+
+    let ruleset_file = RulesetFile::new(Ruleset::new(handled_fs, handled_net));
+    let fd = {
+        let max_fds = max_fds(&ctx.process);
+        let file_table = ctx.thread_local.file_table().borrow();
+        let mut file_table_locked = file_table.write();
+        // Like Linux, ruleset fds are always closed on `execve`.
+        file_table_locked.insert(Arc::new(ruleset_file), FdFlags::CLOEXEC, max_fds)?
+    };
+
+    Ok(SyscallReturn::Return(fd as _))
+}
+
+pub fn sys_landlock_add_rule(
+    ruleset_fd: FileDesc,
+    rule_type: u32,
+    rule_attr_addr: Vaddr,
+    flags: u32,
+    ctx: &Context,
+) -> Result<SyscallReturn> {
+    debug!(
+        "ruleset_fd = {}, rule_type = {}, rule_attr_addr = {:#x}, flags = {:#x}",
+        ruleset_fd, rule_type, rule_attr_addr, flags
+    );
+
+    if flags != 0 {
+        return_errno_with_message!(Errno::EINVAL, "unknown flags");
+    }
+
+    let user_space = ctx.user_space();
+    match rule_type {
+        LANDLOCK_RULE_PATH_BENEATH => {
+            // The attributes are packed (`struct landlock_path_beneath_attr`), so the fields are
+            // read separately.
+            let allowed_access = user_space.read_val::<u64>(rule_attr_addr)?;
+            let parent_fd = user_space.read_val::<FileDesc>(rule_attr_addr + 8)?;
+
+            if allowed_access == 0 {
+                return_errno_with_message!(Errno::ENOMSG, "the rule allows no access rights");
+            }
+            let allowed_access = AccessFs::from_bits(allowed_access)
+                .ok_or_else(|| Error::with_message(Errno::EINVAL, "unknown access rights"))?;
+            let dentry = {
+                let mut file_table = ctx.thread_local.file_table().borrow_mut();
+                let file = get_file_fast!(&mut file_table, parent_fd);
+                file.as_inode_or_err()
+                    .map_err(|_| Error::with_message(Errno::EBADFD, "the file is not a path"))?
+                    .dentry()
+                    .clone()
+            };
+
+            let ruleset_file = get_ruleset_file(ruleset_fd, ctx)?;
+            let ruleset_file = ruleset_file.downcast_ref::<RulesetFile>().unwrap();
+            ruleset_file
+                .ruleset()
+                .lock()
+                .add_path_rule(dentry, allowed_access)?;
+        }
+        LANDLOCK_RULE_NET_PORT => {
+            let attr = user_space.read_val::<CNetPortAttr>(rule_attr_addr)?;
+
+            if attr.allowed_access == 0 {
+                return_errno_with_message!(Errno::ENOMSG, "the rule allows no access rights");
+            }
+            let allowed_access = AccessNet::from_bits(attr.allowed_access)
+                .ok_or_else(|| Error::with_message(Errno::EINVAL, "unknown access rights"))?;
+            let port = u16::try_from(attr.port)
+                .map_err(|_| Error::with_message(Errno::EINVAL, "the port is invalid"))?;
+
+            let ruleset_file = get_ruleset_file(ruleset_fd, ctx)?;
+            let ruleset_file = ruleset_file.downcast_ref::<RulesetFile>().unwrap();
+            ruleset_file
+                .ruleset()
+                .lock()
+                .add_port_rule(port, allowed_access)?;
+        }
+        _ => return_errno_with_message!(Errno::EINVAL, "unknown rule type"),
+    }
+
+    Ok(SyscallReturn::Return(0))
+}
+
+pub fn sys_landlock_restrict_self(
+    ruleset_fd: FileDesc,
+    flags: u32,
+    ctx: &Context,
+) -> Result<SyscallReturn> {
+    debug!("ruleset_fd = {}, flags = {:#x}", ruleset_fd, flags);
+
+    if flags != 0 {
+        return_errno_with_message!(Errno::EINVAL, "unknown flags");
+    }
+
+    // Similar to seccomp, without `no_new_privs`, an unprivileged thread could restrict itself
+    // to trick a set-user-ID program into misbehaving.
+    if !ctx.posix_thread.no_new_privs()
+        && !ctx
+            .posix_thread
+            .credentials()
+            .effective_capset()
+            .contains(CapSet::SYS_ADMIN)
+    {
+        return_errno_with_message!(
+            Errno::EPERM,
+            "restricting the thread requires no_new_privs or CAP_SYS_ADMIN"
+        );
+    }
+
+    let ruleset = {
+        let ruleset_file = get_ruleset_file(ruleset_fd, ctx)?;
+        let ruleset_file = ruleset_file.downcast_ref::<RulesetFile>().unwrap();
+        // The ruleset is copied, so adding rules to it later does not affect the domain.
+        ruleset_file.ruleset().lock().clone()
+    };
+
+    let mut domain = ctx.posix_thread.landlock_domain().lock();
+    *domain = Some(Domain::new(domain.as_deref(), ruleset)?);
+
+    Ok(SyscallReturn::Return(0))
+}
+
+const LANDLOCK_CREATE_RULESET_VERSION: u32 = 1 << 0;
+
+const LANDLOCK_RULE_PATH_BENEATH: u32 = 1;
+const LANDLOCK_RULE_NET_PORT: u32 = 2;
+
+/// The attributes of a ruleset (`struct landlock_ruleset_attr`).
+#[derive(Debug, Clone, Copy, Pod)]
+#[repr(C)]
+struct CRulesetAttr {
+    handled_access_fs: u64,
+    handled_access_net: u64,
+}
+
+/// The attributes of a rule of a TCP port (`struct landlock_net_port_attr`).
+#[derive(Debug, Clone, Copy, Pod)]
+#[repr(C)]
+struct CNetPortAttr {
+    allowed_access: u64,
+    port: u64,
+}
+
+/// Reads the attributes of a ruleset, whose size is specified by the user.
+///
+/// Like other extensible structures, the fields that are unknown to the user are zeros, and the
+/// fields that are unknown to the kernel must be zeros.
+fn read_ruleset_attr(attr_addr: Vaddr, size: usize, ctx: &Context) -> Result<CRulesetAttr> {
+    const MIN_SIZE: usize = core::mem::size_of::<u64>();
+
+    if size < MIN_SIZE {
+        return_errno_with_message!(Errno::EINVAL, "the attributes are too small");
+    }
+    if size > PAGE_SIZE {
+        return_errno_with_message!(Errno::E2BIG, "the attributes are too large");
+    }
+
+    let mut attr = CRulesetAttr::new_zeroed();
+    let known_size = size.min(core::mem::size_of::<CRulesetAttr>());
+    let user_space = ctx.user_space();
+    user_space.read_bytes(
+        attr_addr,
+        &mut VmWriter::from(&mut attr.as_bytes_mut()[..known_size]),
+    )?;
+
+    for addr in attr_addr + known_size..attr_addr + size {
+        if user_space.read_val::<u8>(addr)? != 0 {
+            return_errno_with_message!(Errno::E2BIG, "the attributes contain unknown fields");
+        }
+    }
+
+    Ok(attr)
+}
+
+/// Gets the file of the ruleset, which fails with `EBADFD` if the file is not a ruleset.
+fn get_ruleset_file(ruleset_fd: FileDesc, ctx: &Context) -> Result<Arc<dyn FileLike>> {
+    let mut file_table = ctx.thread_local.file_table().borrow_mut();
+    let file = get_file_fast!(&mut file_table, ruleset_fd).into_owned();
+    if file.downcast_ref::<RulesetFile>().is_none() {
+        return_errno_with_message!(Errno::EBADFD, "the file is not a ruleset");
+    }
+    Ok(file)
+}
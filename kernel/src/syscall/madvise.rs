@@ -1,9 +1,15 @@
 // SPDX-License-Identifier: MPL-2.0
 
+use core::ops::Range;
+
 use align_ext::AlignExt;
+use aster_rights::Full;
 
 use super::SyscallReturn;
-use crate::prelude::*;
+use crate::{
+    prelude::*,
+    vm::{ksm, thp::HugePageAdvice, vmar::Vmar},
+};
 
 pub fn sys_madvise(
     start: Vaddr,
@@ -32,29 +38,40 @@ pub fn sys_madvise(
         Errno::EINVAL,
         "integer overflow when (start + len)",
     ))?;
+    let root_vmar = ctx.thread_local.root_vmar();
+    let advised_range = start..end;
     match behavior {
         MadviseBehavior::MADV_NORMAL
-        | MadviseBehavior::MADV_SEQUENTIAL
-        | MadviseBehavior::MADV_WILLNEED => {
-            // perform a read at first
-            let mut buffer = vec![0u8; len];
-            ctx.user_space()
-                .read_bytes(start, &mut VmWriter::from(buffer.as_mut_slice()))?;
+        | MadviseBehavior::MADV_RANDOM
+        | MadviseBehavior::MADV_SEQUENTIAL => {
+            // The access patterns only affect how many pages are read ahead on page faults,
+            // which is not tunable for now.
+            check_mapped(&root_vmar, &advised_range)?;
+        }
+        MadviseBehavior::MADV_WILLNEED => root_vmar.read_ahead(advised_range)?,
+        MadviseBehavior::MADV_DONTNEED => root_vmar.discard_pages(advised_range)?,
+        MadviseBehavior::MADV_FREE => root_vmar.free_pages(advised_range)?,
+        MadviseBehavior::MADV_MERGEABLE => root_vmar.set_mergeable(advised_range, true)?,
+        MadviseBehavior::MADV_UNMERGEABLE => {
+            ksm::unmerge_range(&ctx.process, advised_range.clone())?;
+            root_vmar.set_mergeable(advised_range, false)?;
+        }
+        MadviseBehavior::MADV_PAGEOUT => root_vmar.page_out(advised_range)?,
+        MadviseBehavior::MADV_HUGEPAGE => {
+            root_vmar.set_huge_page_advice(advised_range, HugePageAdvice::HugePage)?
         }
-        MadviseBehavior::MADV_DONTNEED => {
-            warn!("MADV_DONTNEED isn't implemented, do nothing for now.");
+        MadviseBehavior::MADV_NOHUGEPAGE => {
+            root_vmar.set_huge_page_advice(advised_range, HugePageAdvice::NoHugePage)?
         }
-        MadviseBehavior::MADV_FREE => madv_free(start, end, ctx)?,
-        _ => todo!(),
+        _ => return_errno_with_message!(Errno::EINVAL, "the advice is not supported"),
     }
     Ok(SyscallReturn::Return(0))
 }
 
-fn madv_free(start: Vaddr, end: Vaddr, ctx: &Context) -> Result<()> {
-    let root_vmar = ctx.process.root_vmar();
-    let advised_range = start..end;
-    let _ = root_vmar.remove_mapping(advised_range);
-
+fn check_mapped(root_vmar: &Vmar<Full>, range: &Range<Vaddr>) -> Result<()> {
+    if root_vmar.mapped_size(range.clone()) != range.len() {
+        return_errno_with_message!(Errno::ENOMEM, "the range contains unmapped pages");
+    }
     Ok(())
 }
 
@@ -0,0 +1,260 @@
+// SPDX-License-Identifier: MPL-2.0
+
+use core::mem::size_of;
+
+use align_ext::AlignExt;
+use ostd::mm::numa::{self, NodeSet, MAX_NODES};
+
+use super::SyscallReturn;
+use crate::{
+    prelude::*,
+    process::credentials::capabilities::CapSet,
+    vm::mempolicy::{MemoryPolicy, MemoryPolicyFlags, MemoryPolicyMode},
+};
+
+pub fn sys_set_mempolicy(
+    mode: i32,
+    nodemask_ptr: Vaddr,
+    maxnode: u64,
+    ctx: &Context,
+) -> Result<SyscallReturn> {
+    let (mode, flags) = parse_mode(mode)?;
+    debug!(
+        "mode = {:?}, flags = {:?}, nodemask_ptr = 0x{:x}, maxnode = {}",
+        mode, flags, nodemask_ptr, maxnode
+    );
+
+    let nodes = read_nodes(nodemask_ptr, maxnode, ctx)?;
+    let memory_policy = MemoryPolicy::new(mode, flags, nodes)?;
+    *ctx.posix_thread.memory_policy().lock() = memory_policy;
+
+    Ok(SyscallReturn::Return(0))
+}
+
+pub fn sys_get_mempolicy(
+    mode_ptr: Vaddr,
+    nodemask_ptr: Vaddr,
+    maxnode: u64,
+    addr: Vaddr,
+    flags: u64,
+    ctx: &Context,
+) -> Result<SyscallReturn> {
+    let flags = GetFlags::from_bits(flags)
+        .ok_or_else(|| Error::with_message(Errno::EINVAL, "invalid flags"))?;
+    debug!(
+        "mode_ptr = 0x{:x}, nodemask_ptr = 0x{:x}, maxnode = {}, addr = 0x{:x}, flags = {:?}",
+        mode_ptr, nodemask_ptr, maxnode, addr, flags
+    );
+
+    if nodemask_ptr != 0 && maxnode < numa::num_nodes() as u64 {
+        return_errno_with_message!(Errno::EINVAL, "the node mask is too small");
+    }
+
+    if flags.contains(GetFlags::MEMS_ALLOWED) {
+        if flags.intersects(GetFlags::NODE | GetFlags::ADDR) {
+            return_errno_with_message!(
+                Errno::EINVAL,
+                "the allowed nodes cannot be queried with other flags"
+            );
+        }
+        write_mode(mode_ptr, 0, ctx)?;
+        write_nodes(nodemask_ptr, maxnode, NodeSet::new_full(), ctx)?;
+        return Ok(SyscallReturn::Return(0));
+    }
+
+    let memory_policy = if flags.contains(GetFlags::ADDR) {
+        ctx.thread_local
+            .root_vmar()
+            .memory_policy(addr)?
+            .unwrap_or_else(MemoryPolicy::new_default)
+    } else {
+        if addr != 0 {
+            return_errno_with_message!(
+                Errno::EINVAL,
+                "the address is specified without `MPOL_F_ADDR`"
+            );
+        }
+        ctx.posix_thread.memory_policy().lock().clone()
+    };
+
+    let mode = if flags.contains(GetFlags::NODE) {
+        if flags.contains(GetFlags::ADDR) {
+            ctx.thread_local.root_vmar().node_of_page(addr)? as i32
+        } else if let Some(node) = memory_policy.next_interleave_node() {
+            node as i32
+        } else {
+            return_errno_with_message!(
+                Errno::EINVAL,
+                "the next node can only be queried in the interleave mode"
+            );
+        }
+    } else {
+        memory_policy.mode() as i32 | memory_policy.flags().bits() as i32
+    };
+    write_mode(mode_ptr, mode, ctx)?;
+    write_nodes(
+        nodemask_ptr,
+        maxnode,
+        memory_policy.user_visible_nodes(),
+        ctx,
+    )?;
+
+    Ok(SyscallReturn::Return(0))
+}
+
+pub fn sys_mbind(
+    start: Vaddr,
+    len: usize,
+    mode: i32,
+    nodemask_ptr: Vaddr,
+    maxnode: u64,
+    flags: u32,
+    ctx: &Context,
+) -> Result<SyscallReturn> {
+    let (mode, mode_flags) = parse_mode(mode)?;
+    debug!(
+        "start = 0x{:x}, len = 0x{:x}, mode = {:?}, mode_flags = {:?}, nodemask_ptr = 0x{:x}",
+        start, len, mode, mode_flags, nodemask_ptr
+    );
+    debug!("maxnode = {}, flags = 0x{:x}", maxnode, flags);
+
+    let nodes = read_nodes(nodemask_ptr, maxnode, ctx)?;
+
+    let flags = MbindFlags::from_bits(flags)
+        .ok_or_else(|| Error::with_message(Errno::EINVAL, "invalid flags"))?;
+    if flags.contains(MbindFlags::MOVE_ALL)
+        && !ctx
+            .posix_thread
+            .credentials()
+            .effective_capset()
+            .contains(CapSet::SYS_NICE)
+    {
+        return_errno_with_message!(
+            Errno::EPERM,
+            "moving the pages of other processes requires CAP_SYS_NICE"
+        );
+    }
+
+    if start % PAGE_SIZE != 0 {
+        return_errno_with_message!(Errno::EINVAL, "the start address should be page aligned");
+    }
+    let end = len
+        .checked_add(PAGE_SIZE - 1)
+        .and_then(|len| start.checked_add(len.align_down(PAGE_SIZE)))
+        .ok_or_else(|| Error::with_message(Errno::EINVAL, "the range overflows"))?;
+    if end == start {
+        return Ok(SyscallReturn::Return(0));
+    }
+
+    let memory_policy = match mode {
+        MemoryPolicyMode::Default => {
+            if !nodes.is_empty() {
+                return_errno_with_message!(
+                    Errno::EINVAL,
+                    "the default policy does not accept nodes"
+                );
+            }
+            None
+        }
+        _ => Some(MemoryPolicy::new(mode, mode_flags, nodes)?),
+    };
+    // TODO: Migrate the existing pages that violate the policy if `MPOL_MF_MOVE` or
+    // `MPOL_MF_MOVE_ALL` is specified, or report them if `MPOL_MF_STRICT` is specified.
+    // Currently, the policy only applies to the pages that are allocated later.
+    ctx.thread_local
+        .root_vmar()
+        .set_memory_policy(start..end, memory_policy)?;
+
+    Ok(SyscallReturn::Return(0))
+}
+
+bitflags! {
+    /// The flags of `get_mempolicy`.
+    struct GetFlags: u64 {
+        const NODE         = 1 << 0;
+        const ADDR         = 1 << 1;
+        const MEMS_ALLOWED = 1 << 2;
+    }
+}
+
+bitflags! {
+    /// The flags of `mbind`.
+    struct MbindFlags: u32 {
+        const STRICT   = 1 << 0;
+        const MOVE     = 1 << 1;
+        const MOVE_ALL = 1 << 2;
+    }
+}
+
+/// Splits the mode argument into the mode and the mode flags.
+fn parse_mode(mode: i32) -> Result<(MemoryPolicyMode, MemoryPolicyFlags)> {
+    let mode = mode as u32;
+    let flags = MemoryPolicyFlags::from_bits_truncate(mode);
+    let mode = MemoryPolicyMode::try_from(mode & !MemoryPolicyFlags::all().bits())?;
+    Ok((mode, flags))
+}
+
+// Linux uses `DECLARE_BITMAP` for `nodemask_t`, inside which each part is a `long`.
+type Part = u64;
+const SIZE_OF_PART: usize = size_of::<Part>();
+const NODES_IN_PART: usize = SIZE_OF_PART * 8;
+
+/// Reads the node mask from the user space.
+///
+/// Like Linux, only the first `maxnode - 1` bits are used.
+fn read_nodes(nodemask_ptr: Vaddr, maxnode: u64, ctx: &Context) -> Result<NodeSet> {
+    let nr_bits = maxnode.saturating_sub(1) as usize;
+    if nodemask_ptr == 0 || nr_bits == 0 {
+        return Ok(NodeSet::new_empty());
+    }
+    if nr_bits > PAGE_SIZE * 8 {
+        return_errno_with_message!(Errno::EINVAL, "the node mask is too large");
+    }
+
+    let user_space = ctx.user_space();
+    let mut bits = 0;
+    for part_id in 0..nr_bits.div_ceil(NODES_IN_PART) {
+        let mut part: Part = user_space.read_val(nodemask_ptr + part_id * SIZE_OF_PART)?;
+        let nr_bits_in_part = (nr_bits - part_id * NODES_IN_PART).min(NODES_IN_PART);
+        if nr_bits_in_part < NODES_IN_PART {
+            part &= (1 << nr_bits_in_part) - 1;
+        }
+
+        if part_id * NODES_IN_PART < MAX_NODES {
+            bits = part;
+        } else if part != 0 {
+            return_errno_with_message!(Errno::EINVAL, "the node is not supported");
+        }
+    }
+
+    Ok(NodeSet::from_bits(bits))
+}
+
+/// Writes the node mask to the user space.
+///
+/// Like Linux, `maxnode - 1` bits are written, rounded up to a multiple of the bits in a `long`.
+fn write_nodes(nodemask_ptr: Vaddr, maxnode: u64, nodes: NodeSet, ctx: &Context) -> Result<()> {
+    if nodemask_ptr == 0 {
+        return Ok(());
+    }
+
+    let nr_parts = (maxnode.saturating_sub(1) as usize).div_ceil(NODES_IN_PART);
+    if nr_parts * SIZE_OF_PART > PAGE_SIZE {
+        return_errno_with_message!(Errno::EINVAL, "the node mask is too large");
+    }
+
+    let user_space = ctx.user_space();
+    for part_id in 0..nr_parts {
+        let part: Part = if part_id == 0 { nodes.bits() } else { 0 };
+        user_space.write_val(nodemask_ptr + part_id * SIZE_OF_PART, &part)?;
+    }
+
+    Ok(())
+}
+
+fn write_mode(mode_ptr: Vaddr, mode: i32, ctx: &Context) -> Result<()> {
+    if mode_ptr != 0 {
+        ctx.user_space().write_val(mode_ptr, &mode)?;
+    }
+    Ok(())
+}
@@ -20,22 +20,21 @@ pub fn sys_mkdirat(
     let path = ctx.user_space().read_cstring(path_addr, MAX_FILENAME_LEN)?;
     debug!("dirfd = {}, path = {:?}, mode = {}", dirfd, path, mode);
 
-    let current = ctx.posix_thread;
     let (dir_dentry, name) = {
         let path = path.to_string_lossy();
         if path.is_empty() {
             return_errno_with_message!(Errno::ENOENT, "path is empty");
         }
         let fs_path = FsPath::new(dirfd, path.as_ref())?;
-        current
-            .fs()
+        ctx.thread_local
+            .borrow_fs()
             .resolver()
             .read()
             .lookup_dir_and_new_basename(&fs_path, true)?
     };
 
     let inode_mode = {
-        let mask_mode = mode & !current.fs().umask().read().get();
+        let mask_mode = mode & !ctx.thread_local.borrow_fs().umask().read().get();
         InodeMode::from_bits_truncate(mask_mode)
     };
     let _ = dir_dentry.new_fs_child(name.trim_end_matches('/'), InodeType::Dir, inode_mode)?;
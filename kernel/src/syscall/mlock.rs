@@ -0,0 +1,139 @@
+// SPDX-License-Identifier: MPL-2.0
+
+use core::ops::Range;
+
+use align_ext::AlignExt;
+use ostd::mm::MAX_USERSPACE_VADDR;
+
+use super::SyscallReturn;
+use crate::{prelude::*, vm::vmar::LockMode};
+
+pub fn sys_mlock(start: Vaddr, len: usize, ctx: &Context) -> Result<SyscallReturn> {
+    debug!("start = 0x{:x}, len = 0x{:x}", start, len);
+
+    do_mlock(start, len, LockMode::Populate, ctx)?;
+    Ok(SyscallReturn::Return(0))
+}
+
+pub fn sys_mlock2(start: Vaddr, len: usize, flags: u32, ctx: &Context) -> Result<SyscallReturn> {
+    let flags = MlockFlags::from_bits(flags)
+        .ok_or_else(|| Error::with_message(Errno::EINVAL, "unknown flags"))?;
+    debug!(
+        "start = 0x{:x}, len = 0x{:x}, flags = {:?}",
+        start, len, flags
+    );
+
+    let mode = if flags.contains(MlockFlags::MLOCK_ONFAULT) {
+        LockMode::OnFault
+    } else {
+        LockMode::Populate
+    };
+    do_mlock(start, len, mode, ctx)?;
+    Ok(SyscallReturn::Return(0))
+}
+
+pub fn sys_munlock(start: Vaddr, len: usize, ctx: &Context) -> Result<SyscallReturn> {
+    debug!("start = 0x{:x}, len = 0x{:x}", start, len);
+
+    let Some(range) = lock_range(start, len)? else {
+        return Ok(SyscallReturn::Return(0));
+    };
+    ctx.thread_local.root_vmar().set_locked(range, false)?;
+    Ok(SyscallReturn::Return(0))
+}
+
+pub fn sys_mlockall(flags: u32, ctx: &Context) -> Result<SyscallReturn> {
+    let flags = MlockallFlags::from_bits(flags)
+        .ok_or_else(|| Error::with_message(Errno::EINVAL, "unknown flags"))?;
+    debug!("flags = {:?}", flags);
+
+    if !flags.intersects(MlockallFlags::MCL_CURRENT | MlockallFlags::MCL_FUTURE) {
+        return_errno_with_message!(
+            Errno::EINVAL,
+            "either MCL_CURRENT or MCL_FUTURE should be specified"
+        );
+    }
+
+    let mode = if flags.contains(MlockallFlags::MCL_ONFAULT) {
+        LockMode::OnFault
+    } else {
+        LockMode::Populate
+    };
+    let root_vmar = ctx.thread_local.root_vmar();
+
+    if flags.contains(MlockallFlags::MCL_CURRENT) {
+        let all_range = 0..MAX_USERSPACE_VADDR;
+        let unlocked_size =
+            root_vmar.mapped_size(all_range.clone()) - root_vmar.locked_size(all_range.clone());
+        ctx.process.check_locked_memory_limit(unlocked_size)?;
+
+        root_vmar.set_all_locked(true);
+        if mode == LockMode::Populate {
+            // Like Linux, failures of committing the pages are ignored.
+            let _ = root_vmar.populate(all_range);
+        }
+    } else {
+        // Like Linux, `RLIMIT_MEMLOCK` must not be zero for `MCL_FUTURE`.
+        ctx.process.check_locked_memory_limit(0)?;
+    }
+
+    if flags.contains(MlockallFlags::MCL_FUTURE) {
+        root_vmar.set_future_lock(Some(mode));
+    } else {
+        root_vmar.set_future_lock(None);
+    }
+
+    Ok(SyscallReturn::Return(0))
+}
+
+pub fn sys_munlockall(ctx: &Context) -> Result<SyscallReturn> {
+    let root_vmar = ctx.thread_local.root_vmar();
+    root_vmar.set_all_locked(false);
+    root_vmar.set_future_lock(None);
+    Ok(SyscallReturn::Return(0))
+}
+
+fn do_mlock(start: Vaddr, len: usize, mode: LockMode, ctx: &Context) -> Result<()> {
+    let Some(range) = lock_range(start, len)? else {
+        return Ok(());
+    };
+
+    let root_vmar = ctx.thread_local.root_vmar();
+    let unlocked_size = range.len() - root_vmar.locked_size(range.clone());
+    ctx.process.check_locked_memory_limit(unlocked_size)?;
+
+    root_vmar.set_locked(range.clone(), true)?;
+    if mode == LockMode::Populate {
+        root_vmar.populate(range)?;
+    }
+    Ok(())
+}
+
+/// Returns the page-aligned range to lock or unlock.
+///
+/// Unlike other memory syscalls, the start address does not need to be page aligned.
+fn lock_range(start: Vaddr, len: usize) -> Result<Option<Range<Vaddr>>> {
+    if len == 0 {
+        return Ok(None);
+    }
+
+    let end = start
+        .checked_add(len)
+        .filter(|end| *end <= MAX_USERSPACE_VADDR)
+        .ok_or_else(|| Error::with_message(Errno::ENOMEM, "the range is too large"))?;
+    Ok(Some(start.align_down(PAGE_SIZE)..end.align_up(PAGE_SIZE)))
+}
+
+bitflags! {
+    struct MlockFlags: u32 {
+        const MLOCK_ONFAULT = 0x01;
+    }
+}
+
+bitflags! {
+    struct MlockallFlags: u32 {
+        const MCL_CURRENT = 1;
+        const MCL_FUTURE  = 2;
+        const MCL_ONFAULT = 4;
+    }
+}
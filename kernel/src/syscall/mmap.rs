@@ -10,12 +10,15 @@ use crate::{
     fs::{
         file_handle::FileLike,
         file_table::{get_file_fast, FileDesc},
+        inode_handle::InodeHandle,
     },
     prelude::*,
     vm::{
         perms::VmPerms,
-        vmar::is_userspace_vaddr,
+        thp::HUGE_PAGE_SIZE,
+        vmar::{is_userspace_vaddr, LockMode},
         vmo::{VmoOptions, VmoRightsOp},
+        wx,
     },
 };
 
@@ -46,7 +49,7 @@ fn do_sys_mmap(
     addr: Vaddr,
     len: usize,
     vm_perms: VmPerms,
-    mut option: MMapOptions,
+    option: MMapOptions,
     fd: FileDesc,
     offset: usize,
     ctx: &Context,
@@ -56,10 +59,6 @@ fn do_sys_mmap(
         addr, len, vm_perms, option, fd, offset
     );
 
-    if option.flags.contains(MMapFlags::MAP_FIXED_NOREPLACE) {
-        option.flags.insert(MMapFlags::MAP_FIXED);
-    }
-
     check_option(addr, &option)?;
 
     if len == 0 {
@@ -91,11 +90,37 @@ fn do_sys_mmap(
         vm_perms
     };
 
-    let root_vmar = ctx.process.root_vmar();
+    // With `READ_IMPLIES_EXEC`, `PROT_READ` implies `PROT_EXEC`.
+    let vm_perms = wx::apply_personality(vm_perms, ctx);
+
+    // With `MAP_FIXED`, the new mapping replaces the existing mappings in the range.
+    let replaces_mappings = option.flags.contains(MMapFlags::MAP_FIXED)
+        && !option.flags.contains(MMapFlags::MAP_FIXED_NOREPLACE);
+    let replaced_range = replaces_mappings.then(|| addr..addr + len);
+    ctx.process.check_address_space_limit(len, replaced_range)?;
+
+    let root_vmar = ctx.thread_local.root_vmar();
+
+    // The new mapping is locked if `MAP_LOCKED` is specified or `mlockall(MCL_FUTURE)` has
+    // been called.
+    let lock_mode = if option.flags.contains(MMapFlags::MAP_LOCKED) {
+        Some(LockMode::Populate)
+    } else {
+        root_vmar.future_lock()
+    };
+    if lock_mode.is_some() {
+        ctx.process.check_locked_memory_limit(len)?;
+    }
+
+    wx::check_wx("mmap", &(addr..addr + len), vm_perms, ctx)?;
+
     let vm_map_options = {
         let mut options = root_vmar.new_map(len, vm_perms)?;
         let flags = option.flags;
-        if flags.contains(MMapFlags::MAP_FIXED) {
+        if flags.contains(MMapFlags::MAP_FIXED_NOREPLACE) {
+            // The mapping fails with `EEXIST` if the range is occupied.
+            options = options.offset(addr);
+        } else if flags.contains(MMapFlags::MAP_FIXED) {
             options = options.offset(addr).can_overwrite(true);
         } else if flags.contains(MMapFlags::MAP_32BIT) {
             // TODO: support MAP_32BIT. MAP_32BIT requires the map range to be below 2GB
@@ -121,14 +146,21 @@ fn do_sys_mmap(
                     vmo_options.alloc()?
                 };
                 options = options.vmo(shared_vmo);
+            } else if flags.contains(MMapFlags::MAP_GROWSDOWN) {
+                options = options.grows_down(true);
+            } else if len >= HUGE_PAGE_SIZE
+                && !flags.intersects(MMapFlags::MAP_FIXED | MMapFlags::MAP_FIXED_NOREPLACE)
+            {
+                // Like Linux, large private anonymous mappings are aligned to the huge page
+                // size, so that they can be backed by huge pages.
+                options = options.align(HUGE_PAGE_SIZE);
             }
         } else {
-            let vmo = {
+            let (vmo, vmo_offset) = {
                 let mut file_table = ctx.thread_local.file_table().borrow_mut();
                 let file = get_file_fast!(&mut file_table, fd);
-                let inode_handle = file.as_inode_or_err()?;
 
-                let access_mode = inode_handle.access_mode();
+                let access_mode = file.access_mode();
                 if vm_perms.contains(VmPerms::READ) && !access_mode.is_readable() {
                     return_errno!(Errno::EACCES);
                 }
@@ -139,19 +171,21 @@ fn do_sys_mmap(
                     return_errno!(Errno::EACCES);
                 }
 
-                let inode = inode_handle.dentry().inode();
-                inode
-                    .page_cache()
-                    .ok_or(Error::with_message(
+                if let Some(inode_handle) = file.downcast_ref::<InodeHandle>() {
+                    let inode = inode_handle.dentry().inode();
+                    let page_cache = inode.page_cache().ok_or(Error::with_message(
                         Errno::EBADF,
                         "File does not have page cache",
-                    ))?
-                    .to_dyn()
+                    ))?;
+                    (page_cache.to_dyn(), offset)
+                } else {
+                    file.mmap_vmo(offset)?
+                }
             };
 
             options = options
                 .vmo(vmo)
-                .vmo_offset(offset)
+                .vmo_offset(vmo_offset)
                 .handle_page_faults_around();
         }
 
@@ -159,6 +193,19 @@ fn do_sys_mmap(
     };
 
     let map_addr = vm_map_options.build()?;
+
+    let map_range = map_addr..map_addr + len;
+    if vm_perms.contains(VmPerms::EXEC) && option.flags.contains(MMapFlags::MAP_ANONYMOUS) {
+        wx::audit_execmem("mmap", &map_range, ctx);
+    }
+    if lock_mode.is_some() {
+        root_vmar.set_locked(map_range.clone(), true)?;
+    }
+    if lock_mode == Some(LockMode::Populate) {
+        // Like Linux, failures of committing the pages are ignored.
+        let _ = root_vmar.populate(map_range);
+    }
+
     Ok(map_addr)
 }
 
@@ -167,7 +214,11 @@ fn check_option(addr: Vaddr, option: &MMapOptions) -> Result<()> {
         return_errno_with_message!(Errno::EINVAL, "Invalid mmap type");
     }
 
-    if option.flags().contains(MMapFlags::MAP_FIXED) && !is_userspace_vaddr(addr) {
+    if option
+        .flags()
+        .intersects(MMapFlags::MAP_FIXED | MMapFlags::MAP_FIXED_NOREPLACE)
+        && !is_userspace_vaddr(addr)
+    {
         return_errno_with_message!(Errno::EINVAL, "Invalid mmap fixed addr");
     }
 
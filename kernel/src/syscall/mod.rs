@@ -2,13 +2,18 @@
 
 //! Read the Cpu ctx content then dispatch syscall to corresponding handler
 //! The each sub module contains functions that handle real syscall logic.
+use aster_trace::{
+    events::{SYS_ENTER, SYS_EXIT},
+    trace_event,
+};
 pub use clock_gettime::ClockId;
 use ostd::cpu::UserContext;
 
-use crate::{context::Context, cpu::LinuxAbi, prelude::*};
+use crate::{context::Context, cpu::LinuxAbi, prelude::*, security::audit};
 
 mod accept;
 mod access;
+mod adjtimex;
 mod alarm;
 mod arch;
 mod arch_prctl;
@@ -21,10 +26,14 @@ mod chmod;
 mod chown;
 mod chroot;
 mod clock_gettime;
+mod clock_settime;
 mod clone;
 mod close;
+#[cfg(target_arch = "x86_64")]
+mod compat;
 mod connect;
 mod constants;
+mod copy_file_range;
 mod dup;
 mod epoll;
 mod eventfd;
@@ -37,6 +46,7 @@ mod flock;
 mod fork;
 mod fsync;
 mod futex;
+mod getcpu;
 mod getcwd;
 mod getdents64;
 mod getegid;
@@ -58,22 +68,36 @@ mod getsockopt;
 mod gettid;
 mod gettimeofday;
 mod getuid;
+mod io_uring;
 mod ioctl;
+mod keyctl;
 mod kill;
+mod landlock;
 mod link;
 mod listen;
 mod lseek;
 mod madvise;
+mod mempolicy;
 mod mkdir;
 mod mknod;
+mod mlock;
 mod mmap;
 mod mount;
 mod mprotect;
+mod mqueue;
+mod mremap;
+mod msgctl;
+mod msgget;
+mod msgrcv;
+mod msgsnd;
 mod msync;
 mod munmap;
 mod nanosleep;
 mod open;
 mod pause;
+mod personality;
+mod pidfd_open;
+mod pidfd_send_signal;
 mod pipe;
 mod poll;
 mod prctl;
@@ -81,10 +105,12 @@ mod pread64;
 mod preadv;
 mod prlimit64;
 mod pselect6;
+mod ptrace;
 mod pwrite64;
 mod pwritev;
 mod read;
 mod readlink;
+mod reboot;
 mod recvfrom;
 mod recvmsg;
 mod rename;
@@ -92,10 +118,13 @@ mod rmdir;
 mod rt_sigaction;
 mod rt_sigpending;
 mod rt_sigprocmask;
+mod rt_sigqueueinfo;
 mod rt_sigreturn;
 mod rt_sigsuspend;
 mod sched_affinity;
+mod sched_param;
 mod sched_yield;
+mod seccomp;
 mod select;
 mod semctl;
 mod semget;
@@ -104,13 +133,15 @@ mod sendfile;
 mod sendmsg;
 mod sendto;
 mod set_get_priority;
-mod set_robust_list;
+mod set_get_robust_list;
 mod set_tid_address;
 mod setfsgid;
 mod setfsuid;
 mod setgid;
 mod setgroups;
+mod sethostname;
 mod setitimer;
+mod setns;
 mod setpgid;
 mod setregid;
 mod setresgid;
@@ -118,26 +149,39 @@ mod setresuid;
 mod setreuid;
 mod setsid;
 mod setsockopt;
+mod settimeofday;
 mod setuid;
+mod shmat;
+mod shmctl;
+mod shmdt;
+mod shmget;
 mod shutdown;
 mod sigaltstack;
+mod signalfd;
 mod socket;
 mod socketpair;
+mod splice;
 mod stat;
 mod statfs;
+mod swapon;
 mod symlink;
 mod sync;
 mod sysinfo;
+mod syslog;
 mod tgkill;
 mod time;
 mod timer_create;
 mod timer_settime;
+mod timerfd;
 mod truncate;
 mod umask;
 mod umount;
 mod uname;
 mod unlink;
+mod unshare;
+mod userfaultfd;
 mod utimens;
+mod vmsplice;
 mod wait4;
 mod waitid;
 mod write;
@@ -294,6 +338,7 @@ use impl_syscall_nums_and_dispatch_fn;
 use syscall_handler;
 
 pub struct SyscallArgument {
+    arch: u32,
     syscall_number: u64,
     args: [u64; 6],
 }
@@ -308,37 +353,89 @@ pub enum SyscallReturn {
 }
 
 impl SyscallArgument {
-    fn new_from_context(user_ctx: &UserContext) -> Self {
-        let syscall_number = user_ctx.syscall_num() as u64;
+    fn new_from_context(user_ctx: &UserContext, syscall_number: usize) -> Self {
+        let syscall_number = syscall_number as u64;
         let args = user_ctx.syscall_args().map(|x| x as u64);
         Self {
+            arch: user_ctx.syscall_arch(),
             syscall_number,
             args,
         }
     }
 }
 
+/// The system call number that a tracer sets to skip the system call.
+const SKIPPED_SYSCALL_NUMBER: usize = usize::MAX;
+
 pub fn handle_syscall(ctx: &Context, user_ctx: &mut UserContext) {
-    let syscall_frame = SyscallArgument::new_from_context(user_ctx);
-    let syscall_return = arch::syscall_dispatch(
-        syscall_frame.syscall_number,
-        syscall_frame.args,
+    let ptrace = ctx.posix_thread.ptrace();
+    let syscall_number = ptrace.stop_at_syscall_entry(ctx, user_ctx, user_ctx.syscall_num());
+    if syscall_number == SKIPPED_SYSCALL_NUMBER {
+        // The return value has been set by the tracer.
+        ptrace.stop_at_syscall_exit(ctx, user_ctx, syscall_number);
+        return;
+    }
+
+    // The arguments are read after the syscall-entry-stop, since the tracer may change them.
+    let syscall_frame = SyscallArgument::new_from_context(user_ctx, syscall_number);
+    let tid = ctx.posix_thread.tid();
+    trace_event!(
+        SYS_ENTER,
+        tid,
+        syscall_number,
+        syscall_frame.args[0],
+        syscall_frame.args[1]
+    );
+
+    let syscall_return = seccomp::check_syscall(
         ctx,
         user_ctx,
-    );
+        syscall_frame.arch,
+        syscall_frame.syscall_number,
+        syscall_frame.args,
+    )
+    .unwrap_or_else(|| {
+        // Like Linux, the system calls that are rejected by seccomp are not audited.
+        audit::syscall_entry(
+            ctx,
+            syscall_frame.arch,
+            syscall_frame.syscall_number,
+            syscall_frame.args,
+        );
+        #[cfg(target_arch = "x86_64")]
+        if user_ctx.is_compat_syscall() {
+            return compat::syscall_dispatch(
+                syscall_frame.syscall_number,
+                syscall_frame.args,
+                ctx,
+                user_ctx,
+            );
+        }
+        arch::syscall_dispatch(
+            syscall_frame.syscall_number,
+            syscall_frame.args,
+            ctx,
+            user_ctx,
+        )
+    });
+    audit::syscall_exit(ctx, &syscall_return);
 
     match syscall_return {
         Ok(return_value) => {
             if let SyscallReturn::Return(return_value) = return_value {
                 user_ctx.set_syscall_ret(return_value as usize);
+                trace_event!(SYS_EXIT, tid, syscall_number, return_value);
             }
         }
         Err(err) => {
             debug!("syscall return error: {:?}", err);
             let errno = err.error() as i32;
-            user_ctx.set_syscall_ret((-errno) as usize)
+            user_ctx.set_syscall_ret((-errno) as usize);
+            trace_event!(SYS_EXIT, tid, syscall_number, -errno);
         }
     }
+
+    ptrace.stop_at_syscall_exit(ctx, user_ctx, syscall_number);
 }
 
 #[macro_export]
@@ -361,7 +458,3 @@ macro_rules! log_syscall_entry {
         }
     };
 }
-
-pub(super) fn init() {
-    uname::init();
-}
@@ -3,13 +3,16 @@
 use super::SyscallReturn;
 use crate::{
     fs::{
+        binfmt_misc::BinfmtMiscFs,
+        cgroupfs::CgroupFs,
         exfat::{ExfatFS, ExfatMountOptions},
         ext2::Ext2,
         fs_resolver::{FsPath, AT_FDCWD},
-        path::Dentry,
+        path::{Dentry, MountNode, PropagationType},
         utils::{FileSystem, InodeType},
     },
     prelude::*,
+    process::credentials::capabilities::CapSet,
     syscall::constants::MAX_FILENAME_LEN,
 };
 
@@ -34,13 +37,26 @@ pub fn sys_mount(
         devname, dirname, fstype_addr, mount_flags, data,
     );
 
+    if !ctx
+        .posix_thread
+        .credentials()
+        .effective_capset()
+        .contains(CapSet::SYS_ADMIN)
+    {
+        return_errno_with_message!(Errno::EPERM, "mounting requires CAP_SYS_ADMIN");
+    }
+
     let dst_dentry = {
         let dirname = dirname.to_string_lossy();
         if dirname.is_empty() {
             return_errno_with_message!(Errno::ENOENT, "dirname is empty");
         }
         let fs_path = FsPath::new(AT_FDCWD, dirname.as_ref())?;
-        ctx.posix_thread.fs().resolver().read().lookup(&fs_path)?
+        ctx.thread_local
+            .borrow_fs()
+            .resolver()
+            .read()
+            .lookup(&fs_path)?
     };
 
     if mount_flags.contains(MountFlags::MS_REMOUNT) && mount_flags.contains(MountFlags::MS_BIND) {
@@ -59,7 +75,7 @@ pub fn sys_mount(
         | mount_flags.contains(MountFlags::MS_SLAVE)
         | mount_flags.contains(MountFlags::MS_UNBINDABLE)
     {
-        do_change_type()?;
+        do_change_type(mount_flags, dst_dentry)?;
     } else if mount_flags.contains(MountFlags::MS_MOVE) {
         do_move_mount_old(devname, dst_dentry, ctx)?;
     } else {
@@ -93,7 +109,11 @@ fn do_bind_mount(
             return_errno_with_message!(Errno::ENOENT, "src_name is empty");
         }
         let fs_path = FsPath::new(AT_FDCWD, src_name.as_ref())?;
-        ctx.posix_thread.fs().resolver().read().lookup(&fs_path)?
+        ctx.thread_local
+            .borrow_fs()
+            .resolver()
+            .read()
+            .lookup(&fs_path)?
     };
 
     if src_dentry.type_() != InodeType::Dir {
@@ -104,8 +124,41 @@ fn do_bind_mount(
     Ok(())
 }
 
-fn do_change_type() -> Result<()> {
-    return_errno_with_message!(Errno::EINVAL, "do_change_type is not supported");
+/// Changes the propagation type of a mount.
+///
+/// If `MS_REC` is specified, the propagation type of all the mounts
+/// in the subtree will be changed as well.
+fn do_change_type(flags: MountFlags, target_dentry: Dentry) -> Result<()> {
+    let propagation_flags = flags
+        & (MountFlags::MS_SHARED
+            | MountFlags::MS_PRIVATE
+            | MountFlags::MS_SLAVE
+            | MountFlags::MS_UNBINDABLE);
+    let propagation_type = if propagation_flags == MountFlags::MS_SHARED {
+        PropagationType::Shared
+    } else if propagation_flags == MountFlags::MS_PRIVATE {
+        PropagationType::Private
+    } else if propagation_flags == MountFlags::MS_SLAVE {
+        PropagationType::Slave
+    } else if propagation_flags == MountFlags::MS_UNBINDABLE {
+        PropagationType::Unbindable
+    } else {
+        return_errno_with_message!(Errno::EINVAL, "only one propagation type can be specified");
+    };
+
+    if !target_dentry.is_root_of_mount() {
+        return_errno_with_message!(Errno::EINVAL, "the target is not a mountpoint");
+    }
+
+    let mut stack: Vec<Arc<MountNode>> = vec![target_dentry.mount_node().clone()];
+    while let Some(mount_node) = stack.pop() {
+        mount_node.set_propagation_type(propagation_type);
+        if flags.contains(MountFlags::MS_REC) {
+            stack.extend(mount_node.children());
+        }
+    }
+
+    Ok(())
 }
 
 /// Move a mount from src location to dst location.
@@ -116,7 +169,11 @@ fn do_move_mount_old(src_name: CString, dst_dentry: Dentry, ctx: &Context) -> Re
             return_errno_with_message!(Errno::ENOENT, "src_name is empty");
         }
         let fs_path = FsPath::new(AT_FDCWD, src_name.as_ref())?;
-        ctx.posix_thread.fs().resolver().read().lookup(&fs_path)?
+        ctx.thread_local
+            .borrow_fs()
+            .resolver()
+            .read()
+            .lookup(&fs_path)?
     };
 
     if !src_dentry.is_root_of_mount() {
@@ -146,19 +203,32 @@ fn do_new_mount(
     if fs_type.is_empty() {
         return_errno_with_message!(Errno::EINVAL, "fs_type is empty");
     }
-    let fs = get_fs(fs_type, devname)?;
+    let fs = get_fs(fs_type, devname, ctx)?;
     target_dentry.mount(fs)?;
     Ok(())
 }
 
 /// Get the filesystem by fs_type and devname.
-fn get_fs(fs_type: CString, devname: CString) -> Result<Arc<dyn FileSystem>> {
+fn get_fs(fs_type: CString, devname: CString, ctx: &Context) -> Result<Arc<dyn FileSystem>> {
+    let fs_type = fs_type.to_str().unwrap();
+    // The pseudo filesystems are not backed by devices.
+    if fs_type == "cgroup2" {
+        return Ok(CgroupFs::singleton().clone());
+    }
+    if fs_type == "binfmt_misc" {
+        return Ok(BinfmtMiscFs::singleton().clone());
+    }
+    if fs_type == "mqueue" {
+        // Like Linux, the queues of the caller's IPC namespace are mounted.
+        let ipc_ns = ctx.posix_thread.ns_proxy().lock().ipc_ns().clone();
+        return Ok(ipc_ns.posix_msg_queues().fs().clone());
+    }
+
     let devname = devname.to_str().unwrap();
     let device = match aster_block::get_device(devname) {
         Some(device) => device,
         None => return_errno_with_message!(Errno::ENOENT, "Device does not exist"),
     };
-    let fs_type = fs_type.to_str().unwrap();
     match fs_type {
         "ext2" => {
             let ext2_fs = Ext2::open(device)?;
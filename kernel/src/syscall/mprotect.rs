@@ -1,9 +1,13 @@
 // SPDX-License-Identifier: MPL-2.0
 
 use align_ext::AlignExt;
+use ostd::mm::sync_icache_all;
 
 use super::SyscallReturn;
-use crate::{prelude::*, vm::perms::VmPerms};
+use crate::{
+    prelude::*,
+    vm::{perms::VmPerms, wx},
+};
 
 pub fn sys_mprotect(addr: Vaddr, len: usize, perms: u64, ctx: &Context) -> Result<SyscallReturn> {
     let vm_perms = VmPerms::from_bits_truncate(perms as u32);
@@ -11,7 +15,7 @@ pub fn sys_mprotect(addr: Vaddr, len: usize, perms: u64, ctx: &Context) -> Resul
         "addr = 0x{:x}, len = 0x{:x}, perms = {:?}",
         addr, len, vm_perms
     );
-    let root_vmar = ctx.process.root_vmar();
+    let root_vmar = ctx.thread_local.root_vmar();
 
     // According to linux behavior,
     // <https://elixir.bootlin.com/linux/v6.0.9/source/mm/mprotect.c#L681>,
@@ -31,7 +35,18 @@ pub fn sys_mprotect(addr: Vaddr, len: usize, perms: u64, ctx: &Context) -> Resul
         Errno::ENOMEM,
         "integer overflow when (addr + len)",
     ))?;
-    let range = addr..end;
+
+    // With `PROT_GROWSDOWN`, the protection is applied down to the start of the mapping that
+    // grows down.
+    let start = if perms & PROT_GROWSDOWN != 0 {
+        root_vmar.grows_down_start(addr).ok_or(Error::with_message(
+            Errno::EINVAL,
+            "the address is not in a mapping that grows down",
+        ))?
+    } else {
+        addr
+    };
+    let range = start..end;
 
     // On x86, `PROT_WRITE` implies `PROT_READ`.
     // <https://man7.org/linux/man-pages/man2/mprotect.2.html>
@@ -42,6 +57,22 @@ pub fn sys_mprotect(addr: Vaddr, len: usize, perms: u64, ctx: &Context) -> Resul
         vm_perms
     };
 
-    root_vmar.protect(vm_perms, range)?;
+    // With `READ_IMPLIES_EXEC`, `PROT_READ` implies `PROT_EXEC`.
+    let vm_perms = wx::apply_personality(vm_perms, ctx);
+
+    wx::check_wx("mprotect", &range, vm_perms, ctx)?;
+
+    root_vmar.protect(vm_perms, range.clone())?;
+
+    // The code may have been written to the range, e.g., by a just-in-time compiler. The stale
+    // TLB entries have been flushed by `protect`, but the instruction caches may still be
+    // stale on some architectures.
+    if vm_perms.contains(VmPerms::EXEC) {
+        wx::audit_execmem("mprotect", &range, ctx);
+        sync_icache_all();
+    }
+
     Ok(SyscallReturn::Return(0))
 }
+
+const PROT_GROWSDOWN: u64 = 0x0100_0000;
@@ -0,0 +1,304 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! The system calls of POSIX message queues.
+//!
+//! The names passed to these system calls do not have the leading slashes, which are removed by
+//! the C library. See the `ipc::mqueue` module for the implementation of the queues.
+
+use core::time::Duration;
+
+use super::{constants::MAX_FILENAME_LEN, SyscallReturn};
+use crate::{
+    fs::{
+        file_handle::FileLike,
+        file_table::{get_file_fast, max_fds, FdFlags, FileDesc},
+        inode_handle::InodeHandle,
+        utils::StatusFlags,
+    },
+    ipc::mqueue::{mq_attr, MessageQueue, Notification, NotifyKind, NOTIFY_COOKIE_LEN},
+    net::socket::netlink::NetlinkSocket,
+    prelude::*,
+    process::signal::{
+        c_types::{sigevent_t, SigNotify},
+        sig_num::SigNum,
+    },
+    time::{clocks::RealTimeClock, timer::Timeout, timespec_t, wait::ManagedTimeout},
+};
+
+pub fn sys_mq_open(
+    name_addr: Vaddr,
+    flags: u32,
+    mode: u16,
+    attr_addr: Vaddr,
+    ctx: &Context,
+) -> Result<SyscallReturn> {
+    let name = ctx.user_space().read_cstring(name_addr, MAX_FILENAME_LEN)?;
+    let attr = if attr_addr != 0 {
+        Some(ctx.user_space().read_val::<mq_attr>(attr_addr)?)
+    } else {
+        None
+    };
+    debug!(
+        "name = {:?}, flags = {:#o}, mode = {:#o}, attr = {:?}",
+        name, flags, mode, attr
+    );
+
+    let name = name.to_string_lossy();
+    let mode = mode & !ctx.thread_local.borrow_fs().umask().read().get();
+    let ipc_ns = ctx.posix_thread.ns_proxy().lock().ipc_ns().clone();
+    let inode_handle = ipc_ns
+        .posix_msg_queues()
+        .open(&name, flags, mode, attr.as_ref())?;
+
+    // Like Linux, the queue descriptors are always closed on `execve`.
+    let fd = {
+        let max_fds = max_fds(&ctx.process);
+        let file_table = ctx.thread_local.file_table().borrow();
+        let mut file_table_locked = file_table.write();
+        file_table_locked.insert(Arc::new(inode_handle), FdFlags::CLOEXEC, max_fds)?
+    };
+
+    Ok(SyscallReturn::Return(fd as _))
+}
+
+pub fn sys_mq_unlink(name_addr: Vaddr, ctx: &Context) -> Result<SyscallReturn> {
+    let name = ctx.user_space().read_cstring(name_addr, MAX_FILENAME_LEN)?;
+    debug!("name = {:?}", name);
+
+    let ipc_ns = ctx.posix_thread.ns_proxy().lock().ipc_ns().clone();
+    ipc_ns.posix_msg_queues().unlink(&name.to_string_lossy())?;
+
+    Ok(SyscallReturn::Return(0))
+}
+
+pub fn sys_mq_timedsend(
+    mqdes: FileDesc,
+    msg_ptr: Vaddr,
+    msg_len: usize,
+    msg_prio: u32,
+    abs_timeout_addr: Vaddr,
+    ctx: &Context,
+) -> Result<SyscallReturn> {
+    debug!(
+        "mqdes = {}, msg_ptr = {:#x}, msg_len = {}, msg_prio = {}, abs_timeout_addr = {:#x}",
+        mqdes, msg_ptr, msg_len, msg_prio, abs_timeout_addr
+    );
+
+    let timeout = read_abs_timeout(abs_timeout_addr, ctx)?;
+
+    let mut file_table = ctx.thread_local.file_table().borrow_mut();
+    let file = get_file_fast!(&mut file_table, mqdes);
+    let (queue, inode_handle) = as_queue(file.as_ref())?;
+    if !inode_handle.access_mode().is_writable() {
+        return_errno_with_message!(Errno::EBADF, "the queue is not opened for writing");
+    }
+
+    // Check the size before reading the message to avoid allocating a huge buffer.
+    if msg_len > queue.msg_size() {
+        return_errno_with_message!(Errno::EMSGSIZE, "the message is too long");
+    }
+    let mut text = vec![0u8; msg_len].into_boxed_slice();
+    ctx.user_space()
+        .read_bytes(msg_ptr, &mut VmWriter::from(text.as_mut()))?;
+
+    let is_nonblocking = is_nonblocking(inode_handle);
+    queue
+        .send(text, msg_prio, is_nonblocking, timeout, ctx)
+        .map_err(map_wait_error)?;
+
+    Ok(SyscallReturn::Return(0))
+}
+
+pub fn sys_mq_timedreceive(
+    mqdes: FileDesc,
+    msg_ptr: Vaddr,
+    msg_len: usize,
+    msg_prio_addr: Vaddr,
+    abs_timeout_addr: Vaddr,
+    ctx: &Context,
+) -> Result<SyscallReturn> {
+    debug!(
+        "mqdes = {}, msg_ptr = {:#x}, msg_len = {}, msg_prio_addr = {:#x}, abs_timeout_addr = {:#x}",
+        mqdes, msg_ptr, msg_len, msg_prio_addr, abs_timeout_addr
+    );
+
+    let timeout = read_abs_timeout(abs_timeout_addr, ctx)?;
+
+    let mut file_table = ctx.thread_local.file_table().borrow_mut();
+    let file = get_file_fast!(&mut file_table, mqdes);
+    let (queue, inode_handle) = as_queue(file.as_ref())?;
+    if !inode_handle.access_mode().is_readable() {
+        return_errno_with_message!(Errno::EBADF, "the queue is not opened for reading");
+    }
+
+    let is_nonblocking = is_nonblocking(inode_handle);
+    let (text, priority) = queue
+        .receive(msg_len, is_nonblocking, timeout)
+        .map_err(map_wait_error)?;
+
+    // Like Linux, the message is lost if it cannot be copied to the user space.
+    let user_space = ctx.user_space();
+    user_space.write_bytes(msg_ptr, &mut VmReader::from(text.as_ref()))?;
+    if msg_prio_addr != 0 {
+        user_space.write_val(msg_prio_addr, &priority)?;
+    }
+
+    Ok(SyscallReturn::Return(text.len() as _))
+}
+
+pub fn sys_mq_notify(
+    mqdes: FileDesc,
+    sigevent_addr: Vaddr,
+    ctx: &Context,
+) -> Result<SyscallReturn> {
+    debug!("mqdes = {}, sigevent_addr = {:#x}", mqdes, sigevent_addr);
+
+    let notification = if sigevent_addr != 0 {
+        let sig_event = ctx.user_space().read_val::<sigevent_t>(sigevent_addr)?;
+        let kind = notify_kind_from_sigevent(&sig_event, ctx)?;
+        Some(Notification::new(kind, ctx))
+    } else {
+        None
+    };
+
+    let mut file_table = ctx.thread_local.file_table().borrow_mut();
+    let file = get_file_fast!(&mut file_table, mqdes);
+    let (queue, _) = as_queue(file.as_ref())?;
+    queue.set_notification(notification, ctx)?;
+
+    Ok(SyscallReturn::Return(0))
+}
+
+pub fn sys_mq_getsetattr(
+    mqdes: FileDesc,
+    new_attr_addr: Vaddr,
+    old_attr_addr: Vaddr,
+    ctx: &Context,
+) -> Result<SyscallReturn> {
+    debug!(
+        "mqdes = {}, new_attr_addr = {:#x}, old_attr_addr = {:#x}",
+        mqdes, new_attr_addr, old_attr_addr
+    );
+
+    let new_attr = if new_attr_addr != 0 {
+        let new_attr = ctx.user_space().read_val::<mq_attr>(new_attr_addr)?;
+        if new_attr.mq_flags & !(StatusFlags::O_NONBLOCK.bits() as i64) != 0 {
+            return_errno_with_message!(Errno::EINVAL, "only O_NONBLOCK can be set");
+        }
+        Some(new_attr)
+    } else {
+        None
+    };
+
+    let mut file_table = ctx.thread_local.file_table().borrow_mut();
+    let file = get_file_fast!(&mut file_table, mqdes);
+    let (queue, inode_handle) = as_queue(file.as_ref())?;
+
+    let mut old_attr = queue.attr();
+    let status_flags = inode_handle.status_flags();
+    old_attr.mq_flags = (status_flags & StatusFlags::O_NONBLOCK).bits() as i64;
+
+    if let Some(new_attr) = new_attr {
+        // Only the flags can be changed, and the other attributes are ignored.
+        let new_flags = StatusFlags::from_bits_truncate(new_attr.mq_flags as u32);
+        inode_handle.set_status_flags((status_flags - StatusFlags::O_NONBLOCK) | new_flags)?;
+    }
+
+    if old_attr_addr != 0 {
+        ctx.user_space().write_val(old_attr_addr, &old_attr)?;
+    }
+
+    Ok(SyscallReturn::Return(0))
+}
+
+/// Returns the queue and the inode handle if the file is a queue descriptor.
+fn as_queue(file: &dyn FileLike) -> Result<(&MessageQueue, &InodeHandle)> {
+    let inode_handle = file
+        .downcast_ref::<InodeHandle>()
+        .ok_or_else(|| Error::with_message(Errno::EBADF, "the file is not a message queue"))?;
+    let queue = inode_handle
+        .dentry()
+        .inode()
+        .downcast_ref::<MessageQueue>()
+        .ok_or_else(|| Error::with_message(Errno::EBADF, "the file is not a message queue"))?;
+
+    Ok((queue, inode_handle))
+}
+
+fn is_nonblocking(inode_handle: &InodeHandle) -> bool {
+    inode_handle
+        .status_flags()
+        .contains(StatusFlags::O_NONBLOCK)
+}
+
+/// Reads the absolute timeout measured against `CLOCK_REALTIME`.
+fn read_abs_timeout(addr: Vaddr, ctx: &Context) -> Result<Option<ManagedTimeout<'static>>> {
+    if addr == 0 {
+        return Ok(None);
+    }
+
+    let timespec = ctx.user_space().read_val::<timespec_t>(addr)?;
+    let deadline = Duration::try_from(timespec)?;
+
+    Ok(Some(ManagedTimeout::new_with_manager(
+        Timeout::When(deadline),
+        RealTimeClock::timer_manager(),
+    )))
+}
+
+fn map_wait_error(err: Error) -> Error {
+    match err.error() {
+        Errno::ETIME => Error::new(Errno::ETIMEDOUT),
+        Errno::EINTR => Error::new(Errno::ERESTARTSYS),
+        _ => err,
+    }
+}
+
+fn notify_kind_from_sigevent(sig_event: &sigevent_t, ctx: &Context) -> Result<NotifyKind> {
+    let sigev_notify = SigNotify::try_from(sig_event.sigev_notify)?;
+
+    match sigev_notify {
+        SigNotify::SIGEV_NONE => Ok(NotifyKind::None),
+        SigNotify::SIGEV_SIGNAL => {
+            // Like Linux, the signal number can be zero, in which case no signal is sent.
+            let num = match sig_event.sigev_signo {
+                0 => None,
+                signo => {
+                    let signo = u8::try_from(signo)
+                        .map_err(|_| Error::with_message(Errno::EINVAL, "invalid signal number"))?;
+                    Some(SigNum::try_from(signo)?)
+                }
+            };
+            Ok(NotifyKind::Signal {
+                num,
+                value: sig_event.sigev_value,
+            })
+        }
+        SigNotify::SIGEV_THREAD => {
+            // The C library passes the netlink socket in `sigev_signo` and the cookie in
+            // `sigev_value`.
+            let mut cookie = [0u8; NOTIFY_COOKIE_LEN];
+            ctx.user_space().read_bytes(
+                sig_event.sigev_value.read_ptr(),
+                &mut VmWriter::from(cookie.as_mut_slice()),
+            )?;
+
+            let mut file_table = ctx.thread_local.file_table().borrow_mut();
+            let socket = get_file_fast!(&mut file_table, sig_event.sigev_signo).into_owned();
+            if socket.downcast_ref::<NetlinkSocket>().is_none() {
+                if socket.as_socket().is_some() {
+                    return_errno_with_message!(
+                        Errno::ECONNREFUSED,
+                        "the socket is not a netlink socket"
+                    );
+                }
+                return_errno_with_message!(Errno::ENOTSOCK, "the file is not a socket");
+            }
+
+            Ok(NotifyKind::Thread { socket, cookie })
+        }
+        SigNotify::SIGEV_THREAD_ID => {
+            return_errno_with_message!(Errno::EINVAL, "SIGEV_THREAD_ID is not supported")
+        }
+    }
+}
@@ -0,0 +1,98 @@
+// SPDX-License-Identifier: MPL-2.0
+
+use align_ext::AlignExt;
+
+use super::SyscallReturn;
+use crate::{prelude::*, vm::vmar::is_userspace_vaddr};
+
+pub fn sys_mremap(
+    old_addr: Vaddr,
+    old_size: usize,
+    new_size: usize,
+    flags: i32,
+    new_addr: Vaddr,
+    ctx: &Context,
+) -> Result<SyscallReturn> {
+    let flags = MremapFlags::from_bits(flags as u32)
+        .ok_or_else(|| Error::with_message(Errno::EINVAL, "unknown flags"))?;
+    debug!(
+        "old_addr = 0x{:x}, old_size = 0x{:x}, new_size = 0x{:x}, flags = {:?}, new_addr = 0x{:x}",
+        old_addr, old_size, new_size, flags, new_addr
+    );
+
+    let addr = do_sys_mremap(old_addr, old_size, new_size, flags, new_addr, ctx)?;
+    Ok(SyscallReturn::Return(addr as _))
+}
+
+fn do_sys_mremap(
+    old_addr: Vaddr,
+    old_size: usize,
+    new_size: usize,
+    flags: MremapFlags,
+    new_addr: Vaddr,
+    ctx: &Context,
+) -> Result<Vaddr> {
+    if flags.contains(MremapFlags::MREMAP_FIXED) && !flags.contains(MremapFlags::MREMAP_MAYMOVE) {
+        return_errno_with_message!(
+            Errno::EINVAL,
+            "MREMAP_FIXED cannot be specified without MREMAP_MAYMOVE"
+        );
+    }
+    if old_addr % PAGE_SIZE != 0 {
+        return_errno_with_message!(Errno::EINVAL, "the old address should be page aligned");
+    }
+    if old_size > isize::MAX as usize || new_size > isize::MAX as usize {
+        return_errno_with_message!(Errno::EINVAL, "the size is too large");
+    }
+
+    let old_size = old_size.align_up(PAGE_SIZE);
+    let new_size = new_size.align_up(PAGE_SIZE);
+    if new_size == 0 {
+        return_errno_with_message!(Errno::EINVAL, "the new size cannot be zero");
+    }
+    if old_size == 0 {
+        // TODO: Support duplicating shared mappings with a zero old size.
+        return_errno_with_message!(Errno::EINVAL, "the old size cannot be zero");
+    }
+    let old_end = old_addr.checked_add(old_size).ok_or(Error::with_message(
+        Errno::EFAULT,
+        "integer overflow when (old_addr + old_size)",
+    ))?;
+
+    let new_range = if flags.contains(MremapFlags::MREMAP_FIXED) {
+        if new_addr % PAGE_SIZE != 0 || !is_userspace_vaddr(new_addr) {
+            return_errno_with_message!(Errno::EINVAL, "the new address is invalid");
+        }
+        let Some(new_end) = new_addr.checked_add(new_size) else {
+            return_errno_with_message!(
+                Errno::EINVAL,
+                "integer overflow when (new_addr + new_size)"
+            );
+        };
+        if old_addr < new_end && new_addr < old_end {
+            return_errno_with_message!(Errno::EINVAL, "the new range overlaps with the old range");
+        }
+        Some(new_addr..new_end)
+    } else {
+        None
+    };
+
+    if new_size > old_size {
+        ctx.process
+            .check_address_space_limit(new_size - old_size, new_range.clone())?;
+    }
+
+    ctx.thread_local.root_vmar().remap(
+        old_addr..old_end,
+        new_range.map(|range| range.start),
+        new_size,
+        flags.contains(MremapFlags::MREMAP_MAYMOVE),
+    )
+}
+
+bitflags! {
+    struct MremapFlags: u32 {
+        const MREMAP_MAYMOVE = 1;
+        const MREMAP_FIXED   = 2;
+    }
+}
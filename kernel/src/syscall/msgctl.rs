@@ -0,0 +1,30 @@
+// SPDX-License-Identifier: MPL-2.0
+
+use super::SyscallReturn;
+use crate::{ipc::IpcControlCmd, prelude::*};
+
+pub fn sys_msgctl(msqid: i32, cmd: i32, buf: Vaddr, ctx: &Context) -> Result<SyscallReturn> {
+    if msqid < 0 {
+        return_errno_with_message!(Errno::EINVAL, "the queue ID is invalid");
+    }
+
+    let cmd = IpcControlCmd::try_from(cmd)?;
+    debug!(
+        "[sys_msgctl] msqid = {}, cmd = {:?}, buf = {:#x}",
+        msqid, cmd, buf
+    );
+
+    let ipc_ns = ctx.posix_thread.ns_proxy().lock().ipc_ns().clone();
+    let msg_queues = ipc_ns.msg_queues();
+
+    match cmd {
+        IpcControlCmd::IPC_RMID => msg_queues.remove(msqid, ctx)?,
+        IpcControlCmd::IPC_STAT => {
+            let stat = msg_queues.stat(msqid, ctx)?;
+            ctx.user_space().write_val(buf, &stat)?;
+        }
+        _ => return_errno_with_message!(Errno::EINVAL, "the command is not supported"),
+    }
+
+    Ok(SyscallReturn::Return(0))
+}
@@ -0,0 +1,13 @@
+// SPDX-License-Identifier: MPL-2.0
+
+use super::SyscallReturn;
+use crate::prelude::*;
+
+pub fn sys_msgget(key: i32, msgflg: i32, ctx: &Context) -> Result<SyscallReturn> {
+    debug!("[sys_msgget] key = {}, msgflg = {:#o}", key, msgflg);
+
+    let ipc_ns = ctx.posix_thread.ns_proxy().lock().ipc_ns().clone();
+    let msg_id = ipc_ns.msg_queues().get_or_create(key, msgflg, ctx)?;
+
+    Ok(SyscallReturn::Return(msg_id as isize))
+}
@@ -0,0 +1,38 @@
+// SPDX-License-Identifier: MPL-2.0
+
+use super::SyscallReturn;
+use crate::{ipc::msg::MsgFlags, prelude::*};
+
+pub fn sys_msgrcv(
+    msqid: i32,
+    msgp: Vaddr,
+    msgsz: usize,
+    msgtyp: i64,
+    msgflg: i32,
+    ctx: &Context,
+) -> Result<SyscallReturn> {
+    let flags = MsgFlags::from_bits_truncate(msgflg as u32);
+    debug!(
+        "[sys_msgrcv] msqid = {}, msgp = {:#x}, msgsz = {}, msgtyp = {}, flags = {:?}",
+        msqid, msgp, msgsz, msgtyp, flags
+    );
+
+    if msqid < 0 || msgsz > isize::MAX as usize {
+        return_errno_with_message!(Errno::EINVAL, "the queue ID or the buffer size is invalid");
+    }
+
+    let ipc_ns = ctx.posix_thread.ns_proxy().lock().ipc_ns().clone();
+    let message = ipc_ns
+        .msg_queues()
+        .receive(msqid, msgsz, msgtyp, flags, ctx)?;
+
+    // The message is a `long` type followed by the text.
+    let user_space = ctx.user_space();
+    user_space.write_val(msgp, &message.mtype())?;
+    user_space.write_bytes(
+        msgp + core::mem::size_of::<i64>(),
+        &mut VmReader::from(message.text()),
+    )?;
+
+    Ok(SyscallReturn::Return(message.text().len() as isize))
+}
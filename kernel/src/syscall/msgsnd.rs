@@ -0,0 +1,40 @@
+// SPDX-License-Identifier: MPL-2.0
+
+use super::SyscallReturn;
+use crate::{
+    ipc::msg::{Message, MsgFlags, MSGMAX},
+    prelude::*,
+};
+
+pub fn sys_msgsnd(
+    msqid: i32,
+    msgp: Vaddr,
+    msgsz: usize,
+    msgflg: i32,
+    ctx: &Context,
+) -> Result<SyscallReturn> {
+    let flags = MsgFlags::from_bits_truncate(msgflg as u32);
+    debug!(
+        "[sys_msgsnd] msqid = {}, msgp = {:#x}, msgsz = {}, flags = {:?}",
+        msqid, msgp, msgsz, flags
+    );
+
+    if msqid < 0 || msgsz > MSGMAX {
+        return_errno_with_message!(Errno::EINVAL, "the queue ID or the message size is invalid");
+    }
+
+    // The message is a `long` type followed by the text.
+    let user_space = ctx.user_space();
+    let mtype = user_space.read_val::<i64>(msgp)?;
+    let mut text = vec![0u8; msgsz];
+    user_space.read_bytes(
+        msgp + core::mem::size_of::<i64>(),
+        &mut VmWriter::from(text.as_mut_slice()),
+    )?;
+    let message = Message::new(mtype, text.into_boxed_slice())?;
+
+    let ipc_ns = ctx.posix_thread.ns_proxy().lock().ipc_ns().clone();
+    ipc_ns.msg_queues().send(msqid, message, flags, ctx)?;
+
+    Ok(SyscallReturn::Return(0))
+}
@@ -1,9 +1,56 @@
 // SPDX-License-Identifier: MPL-2.0
 
+use align_ext::AlignExt;
+
 use super::SyscallReturn;
 use crate::prelude::*;
 
-pub fn sys_msync(_start: Vaddr, _size: usize, _flag: i32, _ctx: &Context) -> Result<SyscallReturn> {
-    // TODO: implement real `msync`.
+pub fn sys_msync(start: Vaddr, size: usize, flag: i32, ctx: &Context) -> Result<SyscallReturn> {
+    let flags = MsyncFlags::from_bits(flag as u32)
+        .ok_or_else(|| Error::with_message(Errno::EINVAL, "unknown flags"))?;
+    debug!(
+        "start = 0x{:x}, size = 0x{:x}, flags = {:?}",
+        start, size, flags
+    );
+
+    if start % PAGE_SIZE != 0 {
+        return_errno_with_message!(Errno::EINVAL, "the start address should be page aligned");
+    }
+    if flags.contains(MsyncFlags::MS_ASYNC | MsyncFlags::MS_SYNC) {
+        return_errno_with_message!(
+            Errno::EINVAL,
+            "MS_ASYNC and MS_SYNC cannot be specified at the same time"
+        );
+    }
+    if size > isize::MAX as usize {
+        return_errno_with_message!(Errno::ENOMEM, "size align overflow");
+    }
+
+    let size = size.align_up(PAGE_SIZE);
+    let end = start.checked_add(size).ok_or(Error::with_message(
+        Errno::ENOMEM,
+        "integer overflow when (start + size)",
+    ))?;
+    let range = start..end;
+
+    let root_vmar = ctx.thread_local.root_vmar();
+    if root_vmar.mapped_size(range.clone()) != size {
+        return_errno_with_message!(Errno::ENOMEM, "the range contains unmapped pages");
+    }
+
+    // Like Linux, `MS_ASYNC` is a no-op since the dirty pages are already tracked by the page
+    // cache. `MS_INVALIDATE` is also a no-op since the mappings share the page cache pages.
+    if flags.contains(MsyncFlags::MS_SYNC) {
+        root_vmar.sync(range)?;
+    }
+
     Ok(SyscallReturn::Return(0))
 }
+
+bitflags! {
+    struct MsyncFlags: u32 {
+        const MS_ASYNC      = 1;
+        const MS_INVALIDATE = 2;
+        const MS_SYNC       = 4;
+    }
+}
@@ -23,12 +23,13 @@ pub fn sys_nanosleep(
     ctx: &Context,
 ) -> Result<SyscallReturn> {
     let clockid = ClockId::CLOCK_MONOTONIC;
+    let request_time = read_request_time(request_timespec_addr, ctx)?;
 
     do_clock_nanosleep(
         clockid as clockid_t,
         false,
-        request_timespec_addr,
-        remain_timespec_addr,
+        request_time,
+        |remaining_duration| write_remaining_time(remain_timespec_addr, remaining_duration, ctx),
         ctx,
     )
 }
@@ -47,33 +48,50 @@ pub fn sys_clock_nanosleep(
     } else {
         unreachable!()
     };
+    let request_time = read_request_time(request_timespec_addr, ctx)?;
 
     do_clock_nanosleep(
         clockid,
         is_abs_time,
-        request_timespec_addr,
-        remain_timespec_addr,
+        request_time,
+        |remaining_duration| write_remaining_time(remain_timespec_addr, remaining_duration, ctx),
         ctx,
     )
 }
 
-fn do_clock_nanosleep(
+fn read_request_time(request_timespec_addr: Vaddr, ctx: &Context) -> Result<Duration> {
+    let timespec = ctx
+        .user_space()
+        .read_val::<timespec_t>(request_timespec_addr)?;
+    Duration::try_from(timespec)
+}
+
+fn write_remaining_time(
+    remain_timespec_addr: Vaddr,
+    remaining_duration: Duration,
+    ctx: &Context,
+) -> Result<()> {
+    if remain_timespec_addr == 0 {
+        return Ok(());
+    }
+    let remaining_timespec = timespec_t::from(remaining_duration);
+    ctx.user_space()
+        .write_val(remain_timespec_addr, &remaining_timespec)
+}
+
+/// Sleeps for `request_time` on the clock, or until `request_time` if `is_abs_time` is true.
+///
+/// If a relative sleep is interrupted, the remaining time is reported with `write_remaining`.
+pub(super) fn do_clock_nanosleep(
     clockid: clockid_t,
     is_abs_time: bool,
-    request_timespec_addr: Vaddr,
-    remain_timespec_addr: Vaddr,
+    request_time: Duration,
+    write_remaining: impl FnOnce(Duration) -> Result<()>,
     ctx: &Context,
 ) -> Result<SyscallReturn> {
-    let request_time = {
-        let timespec = ctx
-            .user_space()
-            .read_val::<timespec_t>(request_timespec_addr)?;
-        Duration::try_from(timespec)?
-    };
-
     debug!(
-        "clockid = {:?}, is_abs_time = {}, request_time = {:?}, remain_timespec_addr = 0x{:x}",
-        clockid, is_abs_time, request_time, remain_timespec_addr
+        "clockid = {:?}, is_abs_time = {}, request_time = {:?}",
+        clockid, is_abs_time, request_time
     );
 
     let start_time = read_clock(clockid, ctx)?;
@@ -123,11 +141,8 @@ fn do_clock_nanosleep(
                 return Ok(SyscallReturn::Return(0));
             }
 
-            if remain_timespec_addr != 0 && !is_abs_time {
-                let remaining_duration = (start_time + duration) - end_time;
-                let remaining_timespec = timespec_t::from(remaining_duration);
-                ctx.user_space()
-                    .write_val(remain_timespec_addr, &remaining_timespec)?;
+            if !is_abs_time {
+                write_remaining((start_time + duration) - end_time)?;
             }
 
             return_errno_with_message!(Errno::EINTR, "sleep was interrupted");
@@ -3,11 +3,12 @@
 use super::SyscallReturn;
 use crate::{
     fs::{
-        file_table::{FdFlags, FileDesc},
+        file_table::{max_fds, FdFlags, FileDesc},
         fs_resolver::{FsPath, AT_FDCWD},
         utils::{AccessMode, CreationFlags},
     },
     prelude::*,
+    security::audit,
     syscall::constants::MAX_FILENAME_LEN,
 };
 
@@ -24,13 +25,14 @@ pub fn sys_openat(
         dirfd, path, flags, mode
     );
 
-    let current = ctx.posix_thread;
     let file_handle = {
         let path = path.to_string_lossy();
+        audit::log_path(&path, ctx);
         let fs_path = FsPath::new(dirfd, path.as_ref())?;
-        let mask_mode = mode & !current.fs().umask().read().get();
-        let inode_handle = current
-            .fs()
+        let mask_mode = mode & !ctx.thread_local.borrow_fs().umask().read().get();
+        let inode_handle = ctx
+            .thread_local
+            .borrow_fs()
             .resolver()
             .read()
             .open(&fs_path, flags, mask_mode)
@@ -42,6 +44,7 @@ pub fn sys_openat(
     };
 
     let fd = {
+        let max_fds = max_fds(&ctx.process);
         let file_table = ctx.thread_local.file_table().borrow();
         let mut file_table_locked = file_table.write();
         let fd_flags =
@@ -50,7 +53,7 @@ pub fn sys_openat(
             } else {
                 FdFlags::empty()
             };
-        file_table_locked.insert(file_handle, fd_flags)
+        file_table_locked.insert(file_handle, fd_flags, max_fds)?
     };
 
     Ok(SyscallReturn::Return(fd as _))
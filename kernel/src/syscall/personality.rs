@@ -0,0 +1,17 @@
+// SPDX-License-Identifier: MPL-2.0
+
+use super::SyscallReturn;
+use crate::prelude::*;
+
+/// The value that queries the personality without changing it.
+const PERSONALITY_QUERY: u32 = 0xffff_ffff;
+
+pub fn sys_personality(persona: u32, ctx: &Context) -> Result<SyscallReturn> {
+    debug!("persona = {:#x}", persona);
+
+    let old_persona = ctx.process.personality();
+    if persona != PERSONALITY_QUERY {
+        ctx.process.set_personality(persona);
+    }
+    Ok(SyscallReturn::Return(old_persona as _))
+}
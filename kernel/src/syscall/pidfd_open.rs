@@ -0,0 +1,44 @@
+// SPDX-License-Identifier: MPL-2.0
+
+use super::SyscallReturn;
+use crate::{
+    fs::{
+        file_table::{max_fds, FdFlags},
+        utils::StatusFlags,
+    },
+    prelude::*,
+    process::{process_table, Pid, PidFile},
+};
+
+pub fn sys_pidfd_open(pid: Pid, flags: u32, ctx: &Context) -> Result<SyscallReturn> {
+    let flags = Flags::from_bits(flags)
+        .ok_or_else(|| Error::with_message(Errno::EINVAL, "unknown flags"))?;
+    debug!("pid = {}, flags = {:?}", pid, flags);
+
+    if pid as i32 <= 0 {
+        return_errno_with_message!(Errno::EINVAL, "the PID is invalid");
+    }
+    let process = ctx
+        .process
+        .pid_ns()
+        .global_id(pid)
+        .and_then(process_table::get_process)
+        .ok_or_else(|| Error::with_message(Errno::ESRCH, "the process does not exist"))?;
+
+    let pid_file = PidFile::new(process, flags.contains(Flags::PIDFD_NONBLOCK));
+    let fd = {
+        let max_fds = max_fds(&ctx.process);
+        let file_table = ctx.thread_local.file_table().borrow();
+        let mut file_table_locked = file_table.write();
+        // Like Linux, pidfds are always closed on `execve`.
+        file_table_locked.insert(Arc::new(pid_file), FdFlags::CLOEXEC, max_fds)?
+    };
+
+    Ok(SyscallReturn::Return(fd as _))
+}
+
+bitflags! {
+    struct Flags: u32 {
+        const PIDFD_NONBLOCK = StatusFlags::O_NONBLOCK.bits();
+    }
+}
@@ -0,0 +1,88 @@
+// SPDX-License-Identifier: MPL-2.0
+
+use super::SyscallReturn;
+use crate::{
+    fs::file_table::{get_file_fast, FileDesc},
+    prelude::*,
+    process::{
+        kill_process,
+        signal::{
+            c_types::siginfo_t,
+            sig_num::SigNum,
+            signals::user::{UserInfoSignal, UserSignal, UserSignalKind},
+        },
+        PidFile,
+    },
+};
+
+pub fn sys_pidfd_send_signal(
+    pidfd: FileDesc,
+    sig_num: u64,
+    info_addr: Vaddr,
+    flags: u32,
+    ctx: &Context,
+) -> Result<SyscallReturn> {
+    debug!(
+        "pidfd = {}, sig_num = {}, info_addr = 0x{:x}, flags = {}",
+        pidfd, sig_num, info_addr, flags
+    );
+
+    if flags != 0 {
+        return_errno_with_message!(Errno::EINVAL, "unknown flags");
+    }
+    let sig_num = if sig_num == 0 {
+        None
+    } else {
+        Some(SigNum::try_from(sig_num as u8)?)
+    };
+
+    let process = {
+        let mut file_table = ctx.thread_local.file_table().borrow_mut();
+        let file = get_file_fast!(&mut file_table, pidfd);
+        let pid_file = file
+            .downcast_ref::<PidFile>()
+            .ok_or_else(|| Error::with_message(Errno::EBADF, "the file is not a pidfd"))?;
+        if pid_file.is_reaped() {
+            return_errno_with_message!(Errno::ESRCH, "the process has been reaped");
+        }
+        pid_file.process().clone()
+    };
+
+    // Like Linux, the target process must be visible in the PID namespace of the current
+    // process.
+    if ctx.process.pid_ns().local_id(process.pid()).is_none() {
+        return_errno_with_message!(
+            Errno::EINVAL,
+            "the process is not in the PID namespace of the current process"
+        );
+    }
+
+    if info_addr == 0 {
+        let signal = sig_num.map(|sig_num| {
+            let pid = ctx.process.pid();
+            let uid = ctx.posix_thread.credentials().ruid();
+            UserSignal::new(sig_num, UserSignalKind::Kill, pid, uid)
+        });
+        kill_process(&process, signal, ctx)?;
+        return Ok(SyscallReturn::Return(0));
+    }
+
+    let info = ctx.user_space().read_val::<siginfo_t>(info_addr)?;
+    if sig_num.map_or(0, |sig_num| sig_num.as_u8() as i32) != info.si_signo {
+        return_errno_with_message!(
+            Errno::EINVAL,
+            "the signal number does not match the signal information"
+        );
+    }
+    let signal = sig_num.map(|sig_num| UserInfoSignal::new(sig_num, info));
+    if signal.is_some_and(|signal| signal.is_impersonating()) && process.pid() != ctx.process.pid()
+    {
+        return_errno_with_message!(
+            Errno::EPERM,
+            "the signal information can only be used to send signals to the current process"
+        );
+    }
+    kill_process(&process, signal, ctx)?;
+
+    Ok(SyscallReturn::Return(0))
+}
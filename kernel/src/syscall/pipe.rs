@@ -3,9 +3,9 @@
 use super::SyscallReturn;
 use crate::{
     fs::{
-        file_table::{FdFlags, FileDesc},
+        file_table::{max_fds, FdFlags, FileDesc},
         pipe,
-        utils::CreationFlags,
+        utils::{CreationFlags, StatusFlags},
     },
     prelude::*,
 };
@@ -13,7 +13,15 @@ use crate::{
 pub fn sys_pipe2(fds: Vaddr, flags: u32, ctx: &Context) -> Result<SyscallReturn> {
     debug!("flags: {:?}", flags);
 
-    let (pipe_reader, pipe_writer) = pipe::new_pair()?;
+    let supported_flags =
+        CreationFlags::O_CLOEXEC.bits() | (StatusFlags::O_NONBLOCK | StatusFlags::O_DIRECT).bits();
+    if flags & !supported_flags != 0 {
+        return_errno_with_message!(Errno::EINVAL, "invalid flags for pipe2");
+    }
+
+    let status_flags =
+        StatusFlags::from_bits_truncate(flags) & (StatusFlags::O_NONBLOCK | StatusFlags::O_DIRECT);
+    let (pipe_reader, pipe_writer) = pipe::new_pair_with_flags(status_flags)?;
 
     let fd_flags = if CreationFlags::from_bits_truncate(flags).contains(CreationFlags::O_CLOEXEC) {
         FdFlags::CLOEXEC
@@ -21,12 +29,19 @@ pub fn sys_pipe2(fds: Vaddr, flags: u32, ctx: &Context) -> Result<SyscallReturn>
         FdFlags::empty()
     };
 
+    let max_fds = max_fds(&ctx.process);
     let file_table = ctx.thread_local.file_table().borrow();
     let mut file_table_locked = file_table.write();
 
+    let reader_fd = file_table_locked.insert(pipe_reader, fd_flags, max_fds)?;
+    let writer_fd = file_table_locked
+        .insert(pipe_writer, fd_flags, max_fds)
+        .inspect_err(|_| {
+            file_table_locked.close_file(reader_fd).unwrap();
+        })?;
     let pipe_fds = PipeFds {
-        reader_fd: file_table_locked.insert(pipe_reader, fd_flags),
-        writer_fd: file_table_locked.insert(pipe_writer, fd_flags),
+        reader_fd,
+        writer_fd,
     };
     debug!("pipe_fds: {:?}", pipe_fds);
 
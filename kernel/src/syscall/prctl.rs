@@ -1,9 +1,15 @@
 // SPDX-License-Identifier: MPL-2.0
 
-use super::SyscallReturn;
+use super::{
+    seccomp::{set_mode_filter, set_mode_strict, FilterFlags},
+    SyscallReturn,
+};
 use crate::{
     prelude::*,
-    process::{posix_thread::MAX_THREAD_NAME_LEN, signal::sig_num::SigNum},
+    process::{
+        credentials::capabilities::CapSet, posix_thread::MAX_THREAD_NAME_LEN,
+        signal::sig_num::SigNum,
+    },
 };
 
 pub fn sys_prctl(
@@ -31,15 +37,19 @@ pub fn sys_prctl(
             ctx.user_space().write_val(write_to_addr, &write_val)?;
         }
         PrctlCmd::PR_GET_DUMPABLE => {
-            // TODO: when coredump is supported, return the actual value
-            return Ok(SyscallReturn::Return(Dumpable::Disable as _));
+            let dumpable = if ctx.process.is_dumpable() {
+                Dumpable::User
+            } else {
+                Dumpable::Disable
+            };
+            return Ok(SyscallReturn::Return(dumpable as _));
         }
         PrctlCmd::PR_SET_DUMPABLE(dumpable) => {
             if dumpable != Dumpable::Disable && dumpable != Dumpable::User {
                 return_errno!(Errno::EINVAL)
             }
 
-            // TODO: implement coredump
+            ctx.process.set_dumpable(dumpable == Dumpable::User);
         }
         PrctlCmd::PR_GET_KEEPCAPS => {
             let keep_cap = {
@@ -60,6 +70,50 @@ pub fn sys_prctl(
             let credentials = ctx.posix_thread.credentials_mut();
             credentials.set_keep_capabilities(keep_cap != 0);
         }
+        PrctlCmd::PR_CAPBSET_READ(cap) => {
+            let credentials = ctx.posix_thread.credentials();
+            let is_set = credentials.bounding_capset().contains(cap);
+            return Ok(SyscallReturn::Return(is_set as _));
+        }
+        PrctlCmd::PR_CAPBSET_DROP(cap) => {
+            let credentials = ctx.posix_thread.credentials();
+            if !credentials.effective_capset().contains(CapSet::SETPCAP) {
+                return_errno_with_message!(
+                    Errno::EPERM,
+                    "dropping capabilities from the bounding set requires CAP_SETPCAP"
+                );
+            }
+            let bounding = credentials.bounding_capset() - cap;
+            ctx.posix_thread
+                .credentials_mut()
+                .set_bounding_capset(bounding);
+        }
+        PrctlCmd::PR_CAP_AMBIENT(op) => {
+            let credentials = ctx.posix_thread.credentials();
+            let ambient = credentials.ambient_capset();
+            let new_ambient = match op {
+                CapAmbientOp::IsSet(cap) => {
+                    return Ok(SyscallReturn::Return(ambient.contains(cap) as _));
+                }
+                CapAmbientOp::Raise(cap) => {
+                    // A capability can be ambient only if it is both permitted and inheritable.
+                    if !credentials.permitted_capset().contains(cap)
+                        || !credentials.inheritable_capset().contains(cap)
+                    {
+                        return_errno_with_message!(
+                            Errno::EPERM,
+                            "the capability is not permitted or inheritable"
+                        );
+                    }
+                    ambient | cap
+                }
+                CapAmbientOp::Lower(cap) => ambient - cap,
+                CapAmbientOp::ClearAll => CapSet::empty(),
+            };
+            ctx.posix_thread
+                .credentials_mut()
+                .set_ambient_capset(new_ambient);
+        }
         PrctlCmd::PR_GET_NAME(write_to_addr) => {
             let thread_name = ctx.posix_thread.thread_name().lock();
             if let Some(thread_name) = &*thread_name {
@@ -80,6 +134,32 @@ pub fn sys_prctl(
                 thread_name.set_name(&new_thread_name)?;
             }
         }
+        PrctlCmd::PR_GET_SECCOMP => {
+            let mode = ctx.posix_thread.seccomp().lock().mode();
+            return Ok(SyscallReturn::Return(mode as _));
+        }
+        PrctlCmd::PR_SET_SECCOMP(mode, filter_addr) => {
+            return match mode {
+                SECCOMP_MODE_STRICT => set_mode_strict(ctx),
+                SECCOMP_MODE_FILTER => set_mode_filter(FilterFlags::empty(), filter_addr, ctx),
+                _ => return_errno_with_message!(Errno::EINVAL, "invalid seccomp mode"),
+            };
+        }
+        PrctlCmd::PR_SET_CHILD_SUBREAPER(is_child_subreaper) => {
+            ctx.process.set_child_subreaper(is_child_subreaper);
+        }
+        PrctlCmd::PR_GET_CHILD_SUBREAPER(write_to_addr) => {
+            let is_child_subreaper = ctx.process.is_child_subreaper() as i32;
+            ctx.user_space()
+                .write_val(write_to_addr, &is_child_subreaper)?;
+        }
+        PrctlCmd::PR_SET_NO_NEW_PRIVS => {
+            ctx.posix_thread.set_no_new_privs();
+        }
+        PrctlCmd::PR_GET_NO_NEW_PRIVS => {
+            let no_new_privs = ctx.posix_thread.no_new_privs();
+            return Ok(SyscallReturn::Return(no_new_privs as _));
+        }
         _ => todo!(),
     }
     Ok(SyscallReturn::Return(0))
@@ -93,8 +173,25 @@ const PR_GET_KEEPCAPS: i32 = 7;
 const PR_SET_KEEPCAPS: i32 = 8;
 const PR_SET_NAME: i32 = 15;
 const PR_GET_NAME: i32 = 16;
+const PR_GET_SECCOMP: i32 = 21;
+const PR_SET_SECCOMP: i32 = 22;
+const PR_CAPBSET_READ: i32 = 23;
+const PR_CAPBSET_DROP: i32 = 24;
 const PR_SET_TIMERSLACK: i32 = 29;
 const PR_GET_TIMERSLACK: i32 = 30;
+const PR_SET_CHILD_SUBREAPER: i32 = 36;
+const PR_GET_CHILD_SUBREAPER: i32 = 37;
+const PR_SET_NO_NEW_PRIVS: i32 = 38;
+const PR_GET_NO_NEW_PRIVS: i32 = 39;
+const PR_CAP_AMBIENT: i32 = 47;
+
+const PR_CAP_AMBIENT_IS_SET: u64 = 1;
+const PR_CAP_AMBIENT_RAISE: u64 = 2;
+const PR_CAP_AMBIENT_LOWER: u64 = 3;
+const PR_CAP_AMBIENT_CLEAR_ALL: u64 = 4;
+
+const SECCOMP_MODE_STRICT: u64 = 1;
+const SECCOMP_MODE_FILTER: u64 = 2;
 
 #[allow(non_camel_case_types)]
 #[derive(Debug, Clone, Copy)]
@@ -111,6 +208,24 @@ pub enum PrctlCmd {
     PR_GET_TIMERSLACK,
     PR_SET_DUMPABLE(Dumpable),
     PR_GET_DUMPABLE,
+    PR_GET_SECCOMP,
+    PR_SET_SECCOMP(u64, Vaddr),
+    PR_SET_CHILD_SUBREAPER(bool),
+    PR_GET_CHILD_SUBREAPER(Vaddr),
+    PR_SET_NO_NEW_PRIVS,
+    PR_GET_NO_NEW_PRIVS,
+    PR_CAPBSET_READ(CapSet),
+    PR_CAPBSET_DROP(CapSet),
+    PR_CAP_AMBIENT(CapAmbientOp),
+}
+
+/// The operations of `PR_CAP_AMBIENT`.
+#[derive(Debug, Clone, Copy)]
+pub enum CapAmbientOp {
+    IsSet(CapSet),
+    Raise(CapSet),
+    Lower(CapSet),
+    ClearAll,
 }
 
 #[repr(u64)]
@@ -122,7 +237,7 @@ pub enum Dumpable {
 }
 
 impl PrctlCmd {
-    fn from_args(option: i32, arg2: u64, _arg3: u64, _arg4: u64, _arg5: u64) -> Result<PrctlCmd> {
+    fn from_args(option: i32, arg2: u64, arg3: u64, arg4: u64, arg5: u64) -> Result<PrctlCmd> {
         match option {
             PR_SET_PDEATHSIG => {
                 let signum = SigNum::try_from(arg2 as u8)?;
@@ -137,6 +252,44 @@ impl PrctlCmd {
             PR_SET_TIMERSLACK => todo!(),
             PR_GET_KEEPCAPS => Ok(PrctlCmd::PR_GET_KEEPCAPS),
             PR_SET_KEEPCAPS => Ok(PrctlCmd::PR_SET_KEEPCAPS(arg2 as _)),
+            PR_GET_SECCOMP => Ok(PrctlCmd::PR_GET_SECCOMP),
+            PR_SET_SECCOMP => Ok(PrctlCmd::PR_SET_SECCOMP(arg2, arg3 as _)),
+            PR_SET_CHILD_SUBREAPER => Ok(PrctlCmd::PR_SET_CHILD_SUBREAPER(arg2 != 0)),
+            PR_GET_CHILD_SUBREAPER => Ok(PrctlCmd::PR_GET_CHILD_SUBREAPER(arg2 as _)),
+            PR_SET_NO_NEW_PRIVS => {
+                if arg2 != 1 || arg3 != 0 || arg4 != 0 || arg5 != 0 {
+                    return_errno_with_message!(Errno::EINVAL, "invalid arguments");
+                }
+                Ok(PrctlCmd::PR_SET_NO_NEW_PRIVS)
+            }
+            PR_GET_NO_NEW_PRIVS => {
+                if arg2 != 0 || arg3 != 0 || arg4 != 0 || arg5 != 0 {
+                    return_errno_with_message!(Errno::EINVAL, "invalid arguments");
+                }
+                Ok(PrctlCmd::PR_GET_NO_NEW_PRIVS)
+            }
+            PR_CAPBSET_READ => Ok(PrctlCmd::PR_CAPBSET_READ(cap_from_number(arg2)?)),
+            PR_CAPBSET_DROP => Ok(PrctlCmd::PR_CAPBSET_DROP(cap_from_number(arg2)?)),
+            PR_CAP_AMBIENT => {
+                if arg2 == PR_CAP_AMBIENT_CLEAR_ALL {
+                    if arg3 != 0 || arg4 != 0 || arg5 != 0 {
+                        return_errno_with_message!(Errno::EINVAL, "invalid arguments");
+                    }
+                    return Ok(PrctlCmd::PR_CAP_AMBIENT(CapAmbientOp::ClearAll));
+                }
+
+                if arg4 != 0 || arg5 != 0 {
+                    return_errno_with_message!(Errno::EINVAL, "invalid arguments");
+                }
+                let cap = cap_from_number(arg3)?;
+                let op = match arg2 {
+                    PR_CAP_AMBIENT_IS_SET => CapAmbientOp::IsSet(cap),
+                    PR_CAP_AMBIENT_RAISE => CapAmbientOp::Raise(cap),
+                    PR_CAP_AMBIENT_LOWER => CapAmbientOp::Lower(cap),
+                    _ => return_errno_with_message!(Errno::EINVAL, "invalid ambient operation"),
+                };
+                Ok(PrctlCmd::PR_CAP_AMBIENT(op))
+            }
             _ => {
                 debug!("prctl cmd number: {}", option);
                 return_errno_with_message!(Errno::EINVAL, "unsupported prctl command");
@@ -144,3 +297,11 @@ impl PrctlCmd {
         }
     }
 }
+
+/// Converts a capability number (e.g., `CAP_CHOWN`) to a capability set.
+fn cap_from_number(cap: u64) -> Result<CapSet> {
+    if cap > CapSet::most_significant_bit() as u64 {
+        return_errno_with_message!(Errno::EINVAL, "the capability is invalid");
+    }
+    Ok(CapSet::from_bits_truncate(1 << cap))
+}
@@ -3,15 +3,22 @@
 use super::SyscallReturn;
 use crate::{
     prelude::*,
-    process::{rlimit::RLimit64, Pid, ResourceType},
+    process::{
+        credentials::capabilities::CapSet, posix_thread::AsPosixThread, process_table,
+        rlimit::RLimit64, Pid, Process, ResourceType,
+    },
 };
 
+/// The maximum number of file descriptors, which bounds the hard limit of `RLIMIT_NOFILE`.
+///
+/// Reference: <https://elixir.bootlin.com/linux/v6.13/source/fs/file.c>
+const NR_OPEN: u64 = 1024 * 1024;
+
 pub fn sys_getrlimit(resource: u32, rlim_addr: Vaddr, ctx: &Context) -> Result<SyscallReturn> {
     let resource = ResourceType::try_from(resource)?;
     debug!("resource = {:?}, rlim_addr = 0x{:x}", resource, rlim_addr);
-    let resource_limits = ctx.process.resource_limits().lock();
-    let rlimit = resource_limits.get_rlimit(resource);
-    ctx.user_space().write_val(rlim_addr, rlimit)?;
+    let rlimit = *ctx.process.resource_limits().lock().get_rlimit(resource);
+    ctx.user_space().write_val(rlim_addr, &rlimit)?;
     Ok(SyscallReturn::Return(0))
 }
 
@@ -22,11 +29,7 @@ pub fn sys_setrlimit(resource: u32, new_rlim_addr: Vaddr, ctx: &Context) -> Resu
         resource, new_rlim_addr
     );
     let new_rlimit: RLimit64 = ctx.user_space().read_val(new_rlim_addr)?;
-    if !new_rlimit.is_valid() {
-        return_errno_with_message!(Errno::EINVAL, "invalid rlimit");
-    }
-    let mut resource_limits = ctx.process.resource_limits().lock();
-    *resource_limits.get_rlimit_mut(resource) = new_rlimit;
+    do_prlimit(&ctx.process, resource, Some(new_rlimit), ctx)?;
     Ok(SyscallReturn::Return(0))
 }
 
@@ -42,18 +45,109 @@ pub fn sys_prlimit64(
         "pid = {}, resource = {:?}, new_rlim_addr = 0x{:x}, old_rlim_addr = 0x{:x}",
         pid, resource, new_rlim_addr, old_rlim_addr
     );
-    let mut resource_limits = ctx.process.resource_limits().lock();
-    if old_rlim_addr != 0 {
-        let rlimit = resource_limits.get_rlimit(resource);
-        ctx.user_space().write_val(old_rlim_addr, rlimit)?;
-    }
-    if new_rlim_addr != 0 {
+
+    let new_rlimit = if new_rlim_addr != 0 {
         let new_rlimit: RLimit64 = ctx.user_space().read_val(new_rlim_addr)?;
         debug!("new_rlimit = {:?}", new_rlimit);
-        if !new_rlimit.is_valid() {
-            return_errno_with_message!(Errno::EINVAL, "invalid rlimit");
-        }
-        *resource_limits.get_rlimit_mut(resource) = new_rlimit;
+        Some(new_rlimit)
+    } else {
+        None
+    };
+
+    let process = if pid == 0 {
+        ctx.process.clone()
+    } else {
+        ctx.process
+            .pid_ns()
+            .global_id(pid)
+            .and_then(process_table::get_process)
+            .ok_or_else(|| Error::with_message(Errno::ESRCH, "the process does not exist"))?
+    };
+    if !Arc::ptr_eq(&process, &ctx.process) {
+        check_prlimit_perm(&process, ctx)?;
+    }
+
+    let old_rlimit = do_prlimit(&process, resource, new_rlimit, ctx)?;
+    if old_rlim_addr != 0 {
+        ctx.user_space().write_val(old_rlim_addr, &old_rlimit)?;
     }
     Ok(SyscallReturn::Return(0))
 }
+
+/// Sets the resource limit of the process if `new_rlimit` is not `None`, and returns the old
+/// resource limit.
+fn do_prlimit(
+    process: &Process,
+    resource: ResourceType,
+    new_rlimit: Option<RLimit64>,
+    ctx: &Context,
+) -> Result<RLimit64> {
+    let mut resource_limits = process.resource_limits().lock();
+    let rlimit = resource_limits.get_rlimit_mut(resource);
+    let old_rlimit = *rlimit;
+
+    let Some(new_rlimit) = new_rlimit else {
+        return Ok(old_rlimit);
+    };
+
+    if !new_rlimit.is_valid() {
+        return_errno_with_message!(Errno::EINVAL, "invalid rlimit");
+    }
+    if matches!(resource, ResourceType::RLIMIT_NOFILE) && new_rlimit.get_max() > NR_OPEN {
+        return_errno_with_message!(Errno::EPERM, "the file descriptor limit is too large");
+    }
+    if new_rlimit.get_max() > old_rlimit.get_max()
+        && !ctx
+            .posix_thread
+            .credentials()
+            .effective_capset()
+            .contains(CapSet::SYS_RESOURCE)
+    {
+        return_errno_with_message!(Errno::EPERM, "the hard limit cannot be raised");
+    }
+
+    *rlimit = new_rlimit;
+    if matches!(resource, ResourceType::RLIMIT_CPU) {
+        process.timer_manager().set_cpu_limit(&new_rlimit);
+    }
+
+    Ok(old_rlimit)
+}
+
+/// Checks whether the current thread can get or set the resource limits of the target process.
+///
+/// Like Linux, a thread without `CAP_SYS_RESOURCE` can only access the resource limits of the
+/// processes whose user and group IDs all match its real user and group IDs.
+fn check_prlimit_perm(target: &Process, ctx: &Context) -> Result<()> {
+    let credentials = ctx.posix_thread.credentials();
+    if credentials
+        .effective_capset()
+        .contains(CapSet::SYS_RESOURCE)
+    {
+        return Ok(());
+    }
+
+    let main_thread = target.main_thread();
+    let target_credentials = main_thread.as_posix_thread().unwrap().credentials();
+    let uid = credentials.ruid();
+    let gid = credentials.rgid();
+    let is_same_user = [
+        target_credentials.ruid(),
+        target_credentials.euid(),
+        target_credentials.suid(),
+    ]
+    .iter()
+    .all(|target_uid| *target_uid == uid);
+    let is_same_group = [
+        target_credentials.rgid(),
+        target_credentials.egid(),
+        target_credentials.sgid(),
+    ]
+    .iter()
+    .all(|target_gid| *target_gid == gid);
+    if !is_same_user || !is_same_group {
+        return_errno_with_message!(Errno::EPERM, "the target process is owned by another user");
+    }
+
+    Ok(())
+}
@@ -0,0 +1,271 @@
+// SPDX-License-Identifier: MPL-2.0
+
+use core::mem::size_of;
+
+use super::SyscallReturn;
+use crate::{
+    arch::ptrace::PtraceRegs,
+    current_thread,
+    prelude::*,
+    process::{
+        credentials::capabilities::CapSet,
+        posix_thread::{
+            ptrace::{PtraceOptions, PtraceResumeMode},
+            thread_table, AsPosixThread, PosixThread,
+        },
+        signal::{
+            constants::{SIGKILL, SIGSTOP},
+            sig_num::SigNum,
+            signals::kernel::KernelSignal,
+        },
+        Pid,
+    },
+    thread::Thread,
+};
+
+pub fn sys_ptrace(
+    request: u32,
+    pid: Pid,
+    addr: Vaddr,
+    data: usize,
+    ctx: &Context,
+) -> Result<SyscallReturn> {
+    debug!(
+        "request = {:#x}, pid = {}, addr = {:#x}, data = {:#x}",
+        request, pid, addr, data
+    );
+
+    match request {
+        PTRACE_TRACEME => return trace_me(ctx),
+        PTRACE_ATTACH => return attach(pid, ctx),
+        _ => (),
+    }
+
+    let tracee = get_thread(pid, ctx)?;
+    let posix_thread = tracee.as_posix_thread().unwrap();
+    let ptrace = posix_thread.ptrace();
+    let tracer = ctx.process;
+
+    // `PTRACE_KILL` is the only request that does not require the tracee to be stopped.
+    if request == PTRACE_KILL {
+        if !ptrace.is_traced_by(tracer) {
+            return_errno_with_message!(Errno::ESRCH, "the thread is not traced by the process");
+        }
+        posix_thread.enqueue_signal(Box::new(KernelSignal::new(SIGKILL)));
+        return Ok(SyscallReturn::Return(0));
+    }
+    ptrace.check_stopped_by(tracer)?;
+
+    match request {
+        PTRACE_PEEKTEXT | PTRACE_PEEKDATA => {
+            let mut word = 0usize;
+            posix_thread
+                .process()
+                .root_vmar()
+                .access_remote(addr, word.as_bytes_mut(), false)?;
+            ctx.user_space().write_val(data, &word)?;
+        }
+        PTRACE_POKETEXT | PTRACE_POKEDATA => {
+            let mut word = data;
+            posix_thread
+                .process()
+                .root_vmar()
+                .access_remote(addr, word.as_bytes_mut(), true)?;
+        }
+        PTRACE_PEEKUSER => {
+            let word = ptrace.regs(tracer)?.read_user(addr)?;
+            ctx.user_space().write_val(data, &word)?;
+        }
+        PTRACE_POKEUSER => {
+            let mut regs = ptrace.regs(tracer)?;
+            regs.write_user(addr, data)?;
+            ptrace.set_regs(tracer, regs)?;
+        }
+        #[cfg(target_arch = "x86_64")]
+        PTRACE_GETREGS => {
+            ctx.user_space().write_val(data, &ptrace.regs(tracer)?)?;
+        }
+        #[cfg(target_arch = "x86_64")]
+        PTRACE_SETREGS => {
+            let regs = ctx.user_space().read_val::<PtraceRegs>(data)?;
+            ptrace.set_regs(tracer, regs)?;
+        }
+        PTRACE_GETREGSET => {
+            let iov = read_regset_iov(addr, data, ctx)?;
+            let regs = ptrace.regs(tracer)?;
+            let len = iov.len.min(size_of::<PtraceRegs>());
+            ctx.user_space()
+                .write_bytes(iov.base, &mut VmReader::from(&regs.as_bytes()[..len]))?;
+            ctx.user_space()
+                .write_val(data, &PtraceIoVec { len, ..iov })?;
+        }
+        PTRACE_SETREGSET => {
+            let iov = read_regset_iov(addr, data, ctx)?;
+            if iov.len < size_of::<PtraceRegs>() {
+                return_errno_with_message!(Errno::EINVAL, "the register set is incomplete");
+            }
+            let regs = ctx.user_space().read_val::<PtraceRegs>(iov.base)?;
+            ptrace.set_regs(tracer, regs)?;
+        }
+        PTRACE_CONT => {
+            ptrace.resume(tracer, PtraceResumeMode::Continue, parse_signal(data)?)?;
+        }
+        PTRACE_SYSCALL => {
+            ptrace.resume(tracer, PtraceResumeMode::Syscall, parse_signal(data)?)?;
+        }
+        PTRACE_SINGLESTEP => {
+            ptrace.resume(tracer, PtraceResumeMode::SingleStep, parse_signal(data)?)?;
+        }
+        PTRACE_DETACH => {
+            ptrace.detach(posix_thread.tid(), tracer, parse_signal(data)?)?;
+        }
+        PTRACE_SETOPTIONS => {
+            let options = u32::try_from(data)
+                .ok()
+                .and_then(PtraceOptions::from_bits)
+                .ok_or_else(|| {
+                    Error::with_message(Errno::EINVAL, "invalid or unsupported options")
+                })?;
+            ptrace.set_options(tracer, options)?;
+        }
+        PTRACE_GETEVENTMSG => {
+            ctx.user_space()
+                .write_val(data, &ptrace.event_msg(tracer)?)?;
+        }
+        PTRACE_GETSIGINFO => {
+            ctx.user_space().write_val(data, &ptrace.siginfo(tracer)?)?;
+        }
+        _ => return_errno_with_message!(Errno::EIO, "invalid or unsupported ptrace request"),
+    }
+
+    Ok(SyscallReturn::Return(0))
+}
+
+const PTRACE_TRACEME: u32 = 0;
+const PTRACE_PEEKTEXT: u32 = 1;
+const PTRACE_PEEKDATA: u32 = 2;
+const PTRACE_PEEKUSER: u32 = 3;
+const PTRACE_POKETEXT: u32 = 4;
+const PTRACE_POKEDATA: u32 = 5;
+const PTRACE_POKEUSER: u32 = 6;
+const PTRACE_CONT: u32 = 7;
+const PTRACE_KILL: u32 = 8;
+const PTRACE_SINGLESTEP: u32 = 9;
+#[cfg(target_arch = "x86_64")]
+const PTRACE_GETREGS: u32 = 12;
+#[cfg(target_arch = "x86_64")]
+const PTRACE_SETREGS: u32 = 13;
+const PTRACE_ATTACH: u32 = 16;
+const PTRACE_DETACH: u32 = 17;
+const PTRACE_SYSCALL: u32 = 24;
+const PTRACE_SETOPTIONS: u32 = 0x4200;
+const PTRACE_GETEVENTMSG: u32 = 0x4201;
+const PTRACE_GETSIGINFO: u32 = 0x4202;
+const PTRACE_GETREGSET: u32 = 0x4204;
+const PTRACE_SETREGSET: u32 = 0x4205;
+
+/// The register set of the general-purpose registers.
+const NT_PRSTATUS: usize = 1;
+
+#[derive(Debug, Clone, Copy, Pod)]
+#[repr(C)]
+struct PtraceIoVec {
+    base: Vaddr,
+    len: usize,
+}
+
+/// Makes the parent process trace the current thread.
+fn trace_me(ctx: &Context) -> Result<SyscallReturn> {
+    let Some(parent) = ctx.process.parent().lock().process().upgrade() else {
+        return_errno_with_message!(Errno::EPERM, "the process has no parent");
+    };
+
+    ctx.posix_thread
+        .ptrace()
+        .attach(&current_thread!(), &parent)?;
+    Ok(SyscallReturn::Return(0))
+}
+
+/// Makes the current process trace the thread, and stops the thread with `SIGSTOP`.
+fn attach(pid: Pid, ctx: &Context) -> Result<SyscallReturn> {
+    let tracee = get_thread(pid, ctx)?;
+    let posix_thread = tracee.as_posix_thread().unwrap();
+
+    if core::ptr::eq(posix_thread.process().as_ref(), ctx.process) {
+        return_errno_with_message!(Errno::EPERM, "a process cannot trace its own threads");
+    }
+    check_attach_perm(posix_thread, ctx)?;
+
+    posix_thread
+        .ptrace()
+        .attach(&tracee, &ctx.posix_thread.process())?;
+    posix_thread.enqueue_signal(Box::new(KernelSignal::new(SIGSTOP)));
+    Ok(SyscallReturn::Return(0))
+}
+
+/// Checks whether the current thread can attach to the target thread.
+///
+/// Like Linux, a thread without `CAP_SYS_PTRACE` can only attach to the threads whose user and
+/// group IDs all match its real user and group IDs, and whose process is dumpable.
+fn check_attach_perm(target: &PosixThread, ctx: &Context) -> Result<()> {
+    let credentials = ctx.posix_thread.credentials();
+    if credentials.effective_capset().contains(CapSet::SYS_PTRACE) {
+        return Ok(());
+    }
+
+    if !target.process().is_dumpable() {
+        return_errno_with_message!(Errno::EPERM, "the target process is not dumpable");
+    }
+
+    let target_credentials = target.credentials();
+    let uid = credentials.ruid();
+    let gid = credentials.rgid();
+    let is_same_user = [
+        target_credentials.ruid(),
+        target_credentials.euid(),
+        target_credentials.suid(),
+    ]
+    .iter()
+    .all(|target_uid| *target_uid == uid);
+    let is_same_group = [
+        target_credentials.rgid(),
+        target_credentials.egid(),
+        target_credentials.sgid(),
+    ]
+    .iter()
+    .all(|target_gid| *target_gid == gid);
+    if !is_same_user || !is_same_group {
+        return_errno_with_message!(Errno::EPERM, "the target thread is owned by another user");
+    }
+
+    Ok(())
+}
+
+fn get_thread(pid: Pid, ctx: &Context) -> Result<Arc<Thread>> {
+    ctx.process
+        .pid_ns()
+        .global_id(pid)
+        .and_then(thread_table::get_thread)
+        .ok_or_else(|| Error::with_message(Errno::ESRCH, "the target thread does not exist"))
+}
+
+/// Parses the signal that the tracee is resumed with.
+fn parse_signal(data: usize) -> Result<Option<SigNum>> {
+    if data == 0 {
+        return Ok(None);
+    }
+
+    let sig_num = u8::try_from(data)
+        .ok()
+        .and_then(|sig_num| SigNum::try_from(sig_num).ok())
+        .ok_or_else(|| Error::with_message(Errno::EIO, "the signal is invalid"))?;
+    Ok(Some(sig_num))
+}
+
+fn read_regset_iov(addr: Vaddr, data: Vaddr, ctx: &Context) -> Result<PtraceIoVec> {
+    if addr != NT_PRSTATUS {
+        return_errno_with_message!(Errno::EINVAL, "the register set is not supported");
+    }
+
+    ctx.user_space().read_val::<PtraceIoVec>(data)
+}
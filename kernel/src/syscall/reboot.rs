@@ -0,0 +1,115 @@
+// SPDX-License-Identifier: MPL-2.0
+
+use super::{constants::MAX_FILENAME_LEN, SyscallReturn};
+use crate::{
+    prelude::*,
+    process::{
+        credentials::capabilities::CapSet,
+        namespace::PidNamespace,
+        posix_thread::do_exit,
+        process_table,
+        signal::{constants::SIGKILL, signals::kernel::KernelSignal},
+        TermStatus,
+    },
+};
+
+pub fn sys_reboot(
+    magic: u32,
+    magic2: u32,
+    cmd: u32,
+    arg_addr: Vaddr,
+    ctx: &Context,
+) -> Result<SyscallReturn> {
+    debug!(
+        "magic = {:#x}, magic2 = {:#x}, cmd = {:#x}, arg_addr = {:#x}",
+        magic, magic2, cmd, arg_addr
+    );
+
+    if !ctx
+        .posix_thread
+        .credentials()
+        .effective_capset()
+        .contains(CapSet::SYS_BOOT)
+    {
+        return_errno_with_message!(Errno::EPERM, "rebooting requires CAP_SYS_BOOT");
+    }
+
+    if magic != LINUX_REBOOT_MAGIC1 || !LINUX_REBOOT_MAGIC2S.contains(&magic2) {
+        return_errno_with_message!(Errno::EINVAL, "the magic numbers are invalid");
+    }
+    let cmd = RebootCmd::try_from(cmd)?;
+
+    let pid_ns = ctx.process.pid_ns();
+    if pid_ns.parent().is_some() {
+        reboot_pid_ns(cmd, pid_ns)?;
+        return Ok(SyscallReturn::Return(0));
+    }
+
+    match cmd {
+        // TODO: Support sending `SIGINT` to the init process when Ctrl-Alt-Del is pressed.
+        RebootCmd::CadOn | RebootCmd::CadOff => {}
+        RebootCmd::Restart => {
+            println!("[kernel] Restarting system");
+            ostd::power::restart();
+        }
+        RebootCmd::Restart2 => {
+            let arg = ctx.user_space().read_cstring(arg_addr, MAX_FILENAME_LEN)?;
+            println!(
+                "[kernel] Restarting system with command '{}'",
+                arg.to_string_lossy()
+            );
+            ostd::power::restart();
+        }
+        RebootCmd::Halt => {
+            println!("[kernel] System halted");
+            ostd::power::halt();
+        }
+        RebootCmd::PowerOff => {
+            println!("[kernel] Power down");
+            ostd::power::poweroff();
+        }
+        RebootCmd::SwSuspend | RebootCmd::Kexec => {
+            return_errno_with_message!(Errno::EINVAL, "the command is not supported");
+        }
+    }
+
+    Ok(SyscallReturn::Return(0))
+}
+
+/// Reboots the PID namespace, which kills all the processes in the namespace.
+///
+/// Like Linux, this function does not return if it succeeds.
+fn reboot_pid_ns(cmd: RebootCmd, pid_ns: &Arc<PidNamespace>) -> Result<()> {
+    // FIXME: The init process of the namespace should appear to be killed by `SIGHUP` (for
+    // restarting) or `SIGINT` (for powering off and halting) to its parent.
+    match cmd {
+        RebootCmd::Restart | RebootCmd::Restart2 | RebootCmd::PowerOff | RebootCmd::Halt => {}
+        _ => return_errno_with_message!(Errno::EINVAL, "the command is invalid in the namespace"),
+    }
+
+    if let Some(init_process) = pid_ns
+        .init_process_id()
+        .and_then(process_table::get_process)
+    {
+        init_process.enqueue_signal(KernelSignal::new(SIGKILL));
+    }
+    do_exit(TermStatus::Exited(0));
+
+    Ok(())
+}
+
+const LINUX_REBOOT_MAGIC1: u32 = 0xfee1_dead;
+const LINUX_REBOOT_MAGIC2S: [u32; 4] = [672274793, 85072278, 369367448, 537993216];
+
+#[derive(Debug, Clone, Copy, TryFromInt)]
+#[repr(u32)]
+enum RebootCmd {
+    Restart = 0x0123_4567,
+    Halt = 0xcdef_0123,
+    CadOn = 0x89ab_cdef,
+    CadOff = 0x0000_0000,
+    PowerOff = 0x4321_fedc,
+    Restart2 = 0xa1b2_c3d4,
+    SwSuspend = 0xd000_fce2,
+    Kexec = 0x4558_4543,
+}
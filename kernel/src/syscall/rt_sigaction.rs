@@ -26,22 +26,37 @@ pub fn sys_rt_sigaction(
         return_errno_with_message!(Errno::EINVAL, "sigset size is not equal to 8");
     }
 
-    let mut sig_dispositions = ctx.process.sig_dispositions().lock();
-
-    let old_action = if sig_action_addr != 0 {
-        let sig_action_c = ctx.user_space().read_val::<sigaction_t>(sig_action_addr)?;
-        let sig_action = SigAction::try_from(sig_action_c).unwrap();
-        trace!("sig action = {:?}", sig_action);
-        sig_dispositions.set(sig_num, sig_action)
+    let sig_action_c = if sig_action_addr != 0 {
+        Some(ctx.user_space().read_val::<sigaction_t>(sig_action_addr)?)
     } else {
-        sig_dispositions.get(sig_num)
+        None
     };
 
+    let old_action_c = do_rt_sigaction(sig_num, sig_action_c, ctx);
+
     if old_sig_action_addr != 0 {
-        let old_action_c = old_action.as_c_type();
         ctx.user_space()
             .write_val(old_sig_action_addr, &old_action_c)?;
     }
 
     Ok(SyscallReturn::Return(0))
 }
+
+/// Sets the action of the signal if `sig_action_c` is not `None` and returns the old action.
+pub(super) fn do_rt_sigaction(
+    sig_num: SigNum,
+    sig_action_c: Option<sigaction_t>,
+    ctx: &Context,
+) -> sigaction_t {
+    let mut sig_dispositions = ctx.process.sig_dispositions().lock();
+
+    let old_action = if let Some(sig_action_c) = sig_action_c {
+        let sig_action = SigAction::try_from(sig_action_c).unwrap();
+        trace!("sig action = {:?}", sig_action);
+        sig_dispositions.set(sig_num, sig_action)
+    } else {
+        sig_dispositions.get(sig_num)
+    };
+
+    old_action.as_c_type()
+}
@@ -0,0 +1,97 @@
+// SPDX-License-Identifier: MPL-2.0
+
+use super::SyscallReturn;
+use crate::{
+    prelude::*,
+    process::{
+        kill,
+        signal::{c_types::siginfo_t, sig_num::SigNum, signals::user::UserInfoSignal},
+        tgkill, Pid,
+    },
+    thread::Tid,
+};
+
+/// rt_sigqueueinfo sends a signal with the given information to the process with tgid as its
+/// process ID.
+pub fn sys_rt_sigqueueinfo(
+    tgid: Pid,
+    sig_num: u8,
+    info_addr: Vaddr,
+    ctx: &Context,
+) -> Result<SyscallReturn> {
+    debug!(
+        "tgid = {}, sig_num = {}, info_addr = 0x{:x}",
+        tgid, sig_num, info_addr
+    );
+
+    let pid = ctx.process.pid_ns().global_id(tgid);
+    let is_self = pid == Some(ctx.process.pid());
+    let signal = read_signal_from_user(sig_num, info_addr, is_self, ctx)?;
+
+    let Some(pid) = pid else {
+        return_errno_with_message!(Errno::ESRCH, "the target process does not exist");
+    };
+    kill(pid, signal, ctx)?;
+
+    Ok(SyscallReturn::Return(0))
+}
+
+/// rt_tgsigqueueinfo sends a signal with the given information to the thread with tid as its
+/// thread ID, and tgid as its thread group ID.
+pub fn sys_rt_tgsigqueueinfo(
+    tgid: Pid,
+    tid: Tid,
+    sig_num: u8,
+    info_addr: Vaddr,
+    ctx: &Context,
+) -> Result<SyscallReturn> {
+    debug!(
+        "tgid = {}, tid = {}, sig_num = {}, info_addr = 0x{:x}",
+        tgid, tid, sig_num, info_addr
+    );
+
+    if (tgid as i32) <= 0 || (tid as i32) <= 0 {
+        return_errno_with_message!(Errno::EINVAL, "the thread group ID or thread ID is invalid");
+    }
+
+    let pid_ns = ctx.process.pid_ns();
+    let tid = pid_ns.global_id(tid);
+    let is_self = tid == Some(ctx.posix_thread.tid());
+    let signal = read_signal_from_user(sig_num, info_addr, is_self, ctx)?;
+
+    let Some(tid) = tid else {
+        return_errno_with_message!(Errno::ESRCH, "the target thread does not exist");
+    };
+    let tgid = pid_ns.global_id(tgid).unwrap_or(0);
+    tgkill(tid, Some(tgid), signal, ctx)?;
+
+    Ok(SyscallReturn::Return(0))
+}
+
+fn read_signal_from_user(
+    sig_num: u8,
+    info_addr: Vaddr,
+    is_self: bool,
+    ctx: &Context,
+) -> Result<Option<UserInfoSignal>> {
+    let sig_num = if sig_num == 0 {
+        None
+    } else {
+        Some(SigNum::try_from(sig_num)?)
+    };
+
+    let info = ctx.user_space().read_val::<siginfo_t>(info_addr)?;
+    let Some(sig_num) = sig_num else {
+        return Ok(None);
+    };
+
+    let signal = UserInfoSignal::new(sig_num, info);
+    if signal.is_impersonating() && !is_self {
+        return_errno_with_message!(
+            Errno::EPERM,
+            "the signal information can only be used to send signals to the current process"
+        );
+    }
+
+    Ok(Some(signal))
+}
@@ -5,7 +5,15 @@ use core::sync::atomic::Ordering;
 use ostd::{cpu::UserContext, user::UserContextApi};
 
 use super::SyscallReturn;
-use crate::{prelude::*, process::signal::c_types::ucontext_t};
+use crate::{
+    prelude::*,
+    process::signal::{
+        c_types::ucontext_t,
+        constants::{SIGKILL, SIGSTOP},
+        sig_mask::SigMask,
+        SigStack,
+    },
+};
 
 pub fn sys_rt_sigreturn(ctx: &Context, user_ctx: &mut UserContext) -> Result<SyscallReturn> {
     let Context {
@@ -27,11 +35,12 @@ pub fn sys_rt_sigreturn(ctx: &Context, user_ctx: &mut UserContext) -> Result<Sys
 
     let ucontext = ctx.user_space().read_val::<ucontext_t>(sig_context_addr)?;
 
-    // If the sig stack is active and used by current handler, decrease handler counter.
-    if let Some(sig_stack) = &mut *thread_local.sig_stack().borrow_mut() {
-        let rsp = user_ctx.stack_pointer();
-        if rsp >= sig_stack.base() && rsp <= sig_stack.base() + sig_stack.size() {
-            sig_stack.decrease_handler_counter();
+    // Restore the alternate signal stack, which may have been disabled by `SS_AUTODISARM`. Like
+    // Linux, errors are ignored (e.g., the stack cannot be changed since we are still on it).
+    if let Ok(saved_stack) = SigStack::try_from(ucontext.uc_stack) {
+        let mut sig_stack = thread_local.sig_stack().borrow_mut();
+        if !sig_stack.is_on_stack(user_ctx.stack_pointer()) {
+            *sig_stack = saved_stack;
         }
     }
 
@@ -47,12 +56,12 @@ pub fn sys_rt_sigreturn(ctx: &Context, user_ctx: &mut UserContext) -> Result<Sys
         .gp_regs
         .copy_to_raw(user_ctx.general_regs_mut());
 
-    // unblock sig mask
-    let sig_mask = ucontext.uc_sigmask;
-    let old_mask = posix_thread.sig_mask().load(Ordering::Relaxed);
-    posix_thread
-        .sig_mask()
-        .store(old_mask - sig_mask, Ordering::Relaxed);
+    // Restore the signal mask before the signal handler runs. The mask may have been modified
+    // by the handler, but `SIGKILL` and `SIGSTOP` can never be blocked.
+    let mut sig_mask = SigMask::from(ucontext.uc_sigmask);
+    sig_mask -= SIGKILL;
+    sig_mask -= SIGSTOP;
+    posix_thread.sig_mask().store(sig_mask, Ordering::Relaxed);
 
     Ok(SyscallReturn::NoReturn)
 }
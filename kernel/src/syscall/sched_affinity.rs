@@ -1,11 +1,19 @@
 // SPDX-License-Identifier: MPL-2.0
 
-use core::{cmp, mem};
+use core::{cmp, mem, sync::atomic::Ordering};
 
-use ostd::cpu::{num_cpus, CpuId, CpuSet};
+use ostd::{
+    cpu::{num_cpus, CpuId, CpuSet, PinCurrentCpu},
+    task::disable_preempt,
+};
 
 use super::SyscallReturn;
-use crate::{prelude::*, process::posix_thread::thread_table, thread::Tid};
+use crate::{
+    prelude::*,
+    process::posix_thread::{thread_table, AsPosixThread},
+    sched::cpuset,
+    thread::{Thread, Tid},
+};
 
 pub fn sys_sched_getaffinity(
     tid: Tid,
@@ -15,7 +23,7 @@ pub fn sys_sched_getaffinity(
 ) -> Result<SyscallReturn> {
     let cpu_set = match tid {
         0 => ctx.thread.atomic_cpu_affinity().load(),
-        _ => match thread_table::get_thread(tid) {
+        _ => match get_thread(tid, ctx) {
             Some(thread) => thread.atomic_cpu_affinity().load(),
             None => return Err(Error::with_message(Errno::ESRCH, "thread does not exist")),
         },
@@ -26,10 +34,6 @@ pub fn sys_sched_getaffinity(
     Ok(SyscallReturn::Return(bytes_written as isize))
 }
 
-// TODO: The manual page of `sched_setaffinity` says that if the thread is not
-// running on the CPU specified in the affinity mask, it would be migrated to
-// one of the CPUs specified in the mask. We currently do not support this
-// feature as the scheduler is not ready for migration yet.
 pub fn sys_sched_setaffinity(
     tid: Tid,
     cpuset_size: usize,
@@ -39,18 +43,53 @@ pub fn sys_sched_setaffinity(
     let user_cpu_set = read_cpu_set_from(ctx.user_space(), cpuset_size, cpu_set_ptr)?;
 
     match tid {
-        0 => ctx.thread.atomic_cpu_affinity().store(&user_cpu_set),
-        _ => match thread_table::get_thread(tid) {
-            Some(thread) => {
-                thread.atomic_cpu_affinity().store(&user_cpu_set);
-            }
+        0 => set_cpu_affinity(ctx.thread, &user_cpu_set)?,
+        _ => match get_thread(tid, ctx) {
+            Some(thread) => set_cpu_affinity(&thread, &user_cpu_set)?,
             None => return Err(Error::with_message(Errno::ESRCH, "thread does not exist")),
         },
     }
 
+    // If the current thread is no longer allowed to run on the current CPU, it is migrated to one
+    // of the allowed CPUs when it is switched out. Other threads are migrated when they are
+    // preempted or woken up.
+    let is_allowed = {
+        let preempt_guard = disable_preempt();
+        let current_cpu = preempt_guard.current_cpu();
+        ctx.thread
+            .atomic_cpu_affinity()
+            .contains(current_cpu, Ordering::Relaxed)
+    };
+    if !is_allowed {
+        Thread::yield_now();
+    }
+
     Ok(SyscallReturn::Return(0))
 }
 
+fn get_thread(tid: Tid, ctx: &Context) -> Option<Arc<Thread>> {
+    ctx.process
+        .pid_ns()
+        .global_id(tid)
+        .and_then(thread_table::get_thread)
+}
+
+/// Sets the CPU affinity of the thread, which is confined to the cpuset of its process.
+fn set_cpu_affinity(thread: &Thread, user_cpu_set: &CpuSet) -> Result<()> {
+    let process = thread.as_posix_thread().unwrap().process();
+
+    let cpu_set = cpuset::intersect(user_cpu_set, &process.cpuset().cpus());
+    if cpu_set.is_empty() {
+        return_errno_with_message!(
+            Errno::EINVAL,
+            "the CPUs are not in the cpuset of the process"
+        );
+    }
+    thread.atomic_cpu_affinity().store(&cpu_set);
+
+    Ok(())
+}
+
 // Linux uses `DECLARE_BITMAP` for `cpu_set_t`, inside which each part is a
 // `long`. We use the same scheme to ensure byte endianness compatibility.
 type Part = u64;
@@ -0,0 +1,258 @@
+// SPDX-License-Identifier: MPL-2.0
+
+use core::{sync::atomic::Ordering, time::Duration};
+
+use super::SyscallReturn;
+use crate::{
+    prelude::*,
+    process::{
+        credentials::capabilities::CapSet,
+        posix_thread::{thread_table, AsPosixThread, PosixThread},
+    },
+    sched::{priority::Nice, RealTimePolicy, RtPrio, SchedPolicy},
+    thread::{Thread, Tid},
+    time::timespec_t,
+};
+
+pub fn sys_sched_setscheduler(
+    tid: Tid,
+    policy: i32,
+    param_addr: Vaddr,
+    ctx: &Context,
+) -> Result<SyscallReturn> {
+    let policy = LinuxSchedPolicy::try_from_raw(policy)?;
+    let param = read_sched_param(param_addr, ctx)?;
+    debug!(
+        "tid = {}, policy = {:?}, sched_priority = {}",
+        tid, policy, param.sched_priority
+    );
+
+    let thread = get_thread(tid, ctx)?;
+    set_sched_policy(&thread, policy, param.sched_priority, ctx)?;
+
+    Ok(SyscallReturn::Return(0))
+}
+
+pub fn sys_sched_getscheduler(tid: Tid, ctx: &Context) -> Result<SyscallReturn> {
+    let thread = get_thread(tid, ctx)?;
+    let (policy, _) = LinuxSchedPolicy::from_sched_policy(thread.sched_attr().policy());
+    debug!("tid = {}, policy = {:?}", tid, policy);
+
+    Ok(SyscallReturn::Return(policy as _))
+}
+
+pub fn sys_sched_setparam(tid: Tid, param_addr: Vaddr, ctx: &Context) -> Result<SyscallReturn> {
+    let param = read_sched_param(param_addr, ctx)?;
+    debug!("tid = {}, sched_priority = {}", tid, param.sched_priority);
+
+    let thread = get_thread(tid, ctx)?;
+    let (policy, _) = LinuxSchedPolicy::from_sched_policy(thread.sched_attr().policy());
+    set_sched_policy(&thread, policy, param.sched_priority, ctx)?;
+
+    Ok(SyscallReturn::Return(0))
+}
+
+pub fn sys_sched_getparam(tid: Tid, param_addr: Vaddr, ctx: &Context) -> Result<SyscallReturn> {
+    if param_addr == 0 {
+        return_errno_with_message!(Errno::EINVAL, "the parameter pointer is null");
+    }
+
+    let thread = get_thread(tid, ctx)?;
+    let (_, sched_priority) = LinuxSchedPolicy::from_sched_policy(thread.sched_attr().policy());
+    debug!("tid = {}, sched_priority = {}", tid, sched_priority);
+
+    ctx.user_space()
+        .write_val(param_addr, &CSchedParam { sched_priority })?;
+
+    Ok(SyscallReturn::Return(0))
+}
+
+pub fn sys_sched_get_priority_max(policy: i32, _ctx: &Context) -> Result<SyscallReturn> {
+    let (_, max) = LinuxSchedPolicy::try_from_raw(policy)?.priority_range();
+    Ok(SyscallReturn::Return(max as _))
+}
+
+pub fn sys_sched_get_priority_min(policy: i32, _ctx: &Context) -> Result<SyscallReturn> {
+    let (min, _) = LinuxSchedPolicy::try_from_raw(policy)?.priority_range();
+    Ok(SyscallReturn::Return(min as _))
+}
+
+pub fn sys_sched_rr_get_interval(
+    tid: Tid,
+    interval_addr: Vaddr,
+    ctx: &Context,
+) -> Result<SyscallReturn> {
+    let thread = get_thread(tid, ctx)?;
+    let interval = match thread.sched_attr().policy() {
+        SchedPolicy::RealTime { rt_policy, .. } => rt_policy.time_slice(),
+        // Only the RR threads have fixed time slices.
+        _ => Duration::ZERO,
+    };
+    debug!("tid = {}, interval = {:?}", tid, interval);
+
+    ctx.user_space()
+        .write_val(interval_addr, &timespec_t::from(interval))?;
+
+    Ok(SyscallReturn::Return(0))
+}
+
+/// The scheduling policies of Linux.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, TryFromInt)]
+#[repr(i32)]
+enum LinuxSchedPolicy {
+    Normal = 0,
+    Fifo = 1,
+    RoundRobin = 2,
+    Batch = 3,
+    Idle = 5,
+}
+
+/// The flag that can be ORed into the policy to reset the policy of the children.
+const SCHED_RESET_ON_FORK: i32 = 0x4000_0000;
+
+impl LinuxSchedPolicy {
+    fn try_from_raw(raw: i32) -> Result<Self> {
+        if raw & SCHED_RESET_ON_FORK != 0 {
+            // TODO: Reset the scheduling policy of the children.
+            warn!("`SCHED_RESET_ON_FORK` is not supported");
+        }
+
+        Self::try_from(raw & !SCHED_RESET_ON_FORK)
+            .map_err(|_| Error::with_message(Errno::EINVAL, "the scheduling policy is invalid"))
+    }
+
+    /// Returns the Linux policy and the static priority that correspond to the policy.
+    fn from_sched_policy(policy: SchedPolicy) -> (Self, i32) {
+        match policy {
+            // The stop class is more urgent than any real-time threads.
+            SchedPolicy::Stop => (Self::Fifo, 99),
+            SchedPolicy::RealTime { rt_prio, rt_policy } => {
+                let linux_policy = match rt_policy {
+                    RealTimePolicy::Fifo => Self::Fifo,
+                    RealTimePolicy::RoundRobin { .. } => Self::RoundRobin,
+                };
+                // A lower value of `rt_prio` means a higher priority, which is the opposite of
+                // Linux's static priority.
+                (linux_policy, 100 - rt_prio.get() as i32)
+            }
+            SchedPolicy::Fair(_) => (Self::Normal, 0),
+            SchedPolicy::Idle => (Self::Idle, 0),
+        }
+    }
+
+    /// Returns the minimum and maximum static priorities of the policy.
+    fn priority_range(self) -> (i32, i32) {
+        match self {
+            Self::Fifo | Self::RoundRobin => (1, 99),
+            Self::Normal | Self::Batch | Self::Idle => (0, 0),
+        }
+    }
+}
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Pod)]
+struct CSchedParam {
+    sched_priority: i32,
+}
+
+fn read_sched_param(param_addr: Vaddr, ctx: &Context) -> Result<CSchedParam> {
+    if param_addr == 0 {
+        return_errno_with_message!(Errno::EINVAL, "the parameter pointer is null");
+    }
+
+    ctx.user_space().read_val(param_addr)
+}
+
+fn get_thread(tid: Tid, ctx: &Context) -> Result<Arc<Thread>> {
+    if (tid as i32) < 0 {
+        return_errno_with_message!(Errno::EINVAL, "the thread ID is negative");
+    }
+
+    if tid == 0 || tid == ctx.posix_thread.ns_tid() {
+        return Ok(current_thread!());
+    }
+
+    ctx.process
+        .pid_ns()
+        .global_id(tid)
+        .and_then(thread_table::get_thread)
+        .ok_or_else(|| Error::with_message(Errno::ESRCH, "the thread does not exist"))
+}
+
+fn set_sched_policy(
+    thread: &Thread,
+    policy: LinuxSchedPolicy,
+    sched_priority: i32,
+    ctx: &Context,
+) -> Result<()> {
+    let (min, max) = policy.priority_range();
+    if !(min..=max).contains(&sched_priority) {
+        return_errno_with_message!(
+            Errno::EINVAL,
+            "the priority is out of the range of the policy"
+        );
+    }
+
+    let posix_thread = thread.as_posix_thread().unwrap();
+    check_sched_perm(posix_thread, policy, ctx)?;
+
+    let sched_policy = match policy {
+        LinuxSchedPolicy::Normal | LinuxSchedPolicy::Batch => {
+            // The nice value is shared by the threads in the process.
+            let nice: Nice = posix_thread.process().nice().load(Ordering::Relaxed);
+            SchedPolicy::Fair(nice)
+        }
+        LinuxSchedPolicy::Fifo | LinuxSchedPolicy::RoundRobin => {
+            let rt_prio = RtPrio::new((100 - sched_priority) as u8);
+            let rt_policy = if policy == LinuxSchedPolicy::Fifo {
+                RealTimePolicy::Fifo
+            } else {
+                RealTimePolicy::RoundRobin {
+                    base_slice_factor: None,
+                }
+            };
+            SchedPolicy::RealTime { rt_prio, rt_policy }
+        }
+        LinuxSchedPolicy::Idle => {
+            // TODO: Support `SCHED_IDLE`. The idle scheduling class only accepts the per-CPU idle
+            // threads for now.
+            return_errno_with_message!(Errno::EINVAL, "`SCHED_IDLE` is not supported");
+        }
+    };
+
+    thread.sched_attr().set_policy(sched_policy);
+
+    Ok(())
+}
+
+/// Checks whether the current thread can change the scheduling policy of the target thread.
+///
+/// Like Linux, a thread without `CAP_SYS_NICE` can only change the policies of the threads that
+/// are owned by the same user, and cannot switch to the real-time policies.
+//
+// TODO: Allow the unprivileged threads to use the real-time policies according to
+// `RLIMIT_RTPRIO`.
+fn check_sched_perm(target: &PosixThread, policy: LinuxSchedPolicy, ctx: &Context) -> Result<()> {
+    let credentials = ctx.posix_thread.credentials();
+    if credentials.effective_capset().contains(CapSet::SYS_NICE) {
+        return Ok(());
+    }
+
+    let target_credentials = target.credentials();
+    let euid = credentials.euid();
+    if euid != target_credentials.euid() && euid != target_credentials.ruid() {
+        return_errno_with_message!(Errno::EPERM, "the target thread is owned by another user");
+    }
+
+    if matches!(
+        policy,
+        LinuxSchedPolicy::Fifo | LinuxSchedPolicy::RoundRobin
+    ) {
+        return_errno_with_message!(
+            Errno::EPERM,
+            "the real-time policies require the `CAP_SYS_NICE` capability"
+        );
+    }
+
+    Ok(())
+}
@@ -0,0 +1,285 @@
+// SPDX-License-Identifier: MPL-2.0
+
+use core::sync::atomic::Ordering;
+
+use ostd::{cpu::UserContext, user::UserContextApi};
+
+#[cfg(target_arch = "x86_64")]
+use super::{arch::SYS_RT_SIGRETURN, compat};
+use super::{
+    arch::{SYS_EXIT, SYS_READ, SYS_WRITE},
+    SyscallReturn,
+};
+use crate::{
+    prelude::*,
+    process::{
+        credentials::capabilities::CapSet,
+        posix_thread::{
+            do_exit, do_exit_group,
+            seccomp::{SeccompAction, SeccompData, SeccompFilter, SeccompMode},
+            AsPosixThread,
+        },
+        signal::{
+            constants::{SIGKILL, SIGSYS},
+            sig_action::SigAction,
+            signals::seccomp::SeccompSignal,
+        },
+        TermStatus,
+    },
+    util::bpf::CSockFprog,
+};
+
+pub fn sys_seccomp(op: u32, flags: u32, args: Vaddr, ctx: &Context) -> Result<SyscallReturn> {
+    debug!("op = {}, flags = {:#x}, args = {:#x}", op, flags, args);
+
+    match op {
+        SECCOMP_SET_MODE_STRICT => {
+            if flags != 0 || args != 0 {
+                return_errno_with_message!(
+                    Errno::EINVAL,
+                    "the strict mode does not accept flags or arguments"
+                );
+            }
+            set_mode_strict(ctx)
+        }
+        SECCOMP_SET_MODE_FILTER => {
+            let flags = FilterFlags::from_bits(flags)
+                .ok_or_else(|| Error::with_message(Errno::EINVAL, "invalid filter flags"))?;
+            set_mode_filter(flags, args, ctx)
+        }
+        SECCOMP_GET_ACTION_AVAIL => {
+            if flags != 0 {
+                return_errno_with_message!(Errno::EINVAL, "the operation does not accept flags");
+            }
+            let action = ctx.user_space().read_val::<u32>(args)?;
+            if !SeccompAction::is_available(action) {
+                return_errno_with_message!(Errno::EOPNOTSUPP, "the action is not available");
+            }
+            Ok(SyscallReturn::Return(0))
+        }
+        _ => return_errno_with_message!(Errno::EINVAL, "invalid or unsupported seccomp operation"),
+    }
+}
+
+const SECCOMP_SET_MODE_STRICT: u32 = 0;
+const SECCOMP_SET_MODE_FILTER: u32 = 1;
+const SECCOMP_GET_ACTION_AVAIL: u32 = 2;
+
+bitflags! {
+    pub(super) struct FilterFlags: u32 {
+        const TSYNC = 1 << 0;
+        const LOG = 1 << 1;
+        const SPEC_ALLOW = 1 << 2;
+        const NEW_LISTENER = 1 << 3;
+        const TSYNC_ESRCH = 1 << 4;
+        const WAIT_KILLABLE_RECV = 1 << 5;
+    }
+}
+
+/// Enters the strict mode.
+pub(super) fn set_mode_strict(ctx: &Context) -> Result<SyscallReturn> {
+    let _tasks = ctx.process.tasks().lock();
+    ctx.posix_thread.seccomp().lock().set_strict()?;
+
+    Ok(SyscallReturn::Return(0))
+}
+
+/// Installs the filter at `fprog_addr` for the current thread.
+pub(super) fn set_mode_filter(
+    flags: FilterFlags,
+    fprog_addr: Vaddr,
+    ctx: &Context,
+) -> Result<SyscallReturn> {
+    if flags.intersects(FilterFlags::NEW_LISTENER | FilterFlags::WAIT_KILLABLE_RECV) {
+        return_errno_with_message!(Errno::EINVAL, "user notifications are not supported");
+    }
+    // `FilterFlags::LOG` and `FilterFlags::SPEC_ALLOW` are accepted but have no effects, since
+    // we neither log the actions of the filters nor mitigate the speculative execution attacks.
+
+    // Without `no_new_privs`, an unprivileged thread could install a filter that tricks a
+    // set-user-ID program into misbehaving.
+    if !ctx.posix_thread.no_new_privs()
+        && !ctx
+            .posix_thread
+            .credentials()
+            .effective_capset()
+            .contains(CapSet::SYS_ADMIN)
+    {
+        return_errno_with_message!(
+            Errno::EACCES,
+            "installing filters requires no_new_privs or CAP_SYS_ADMIN"
+        );
+    }
+
+    let insns = ctx
+        .user_space()
+        .read_val::<CSockFprog>(fprog_addr)?
+        .read_insns()?;
+
+    // The seccomp states of the threads in a process are only changed with the task lock held,
+    // so that they can be synchronized with `FilterFlags::TSYNC`.
+    let tasks = ctx.process.tasks().lock();
+    let mut seccomp = ctx.posix_thread.seccomp().lock();
+    let filter = SeccompFilter::new(insns, seccomp.filter().cloned())?;
+
+    if !flags.contains(FilterFlags::TSYNC) {
+        seccomp.set_filter(filter)?;
+        return Ok(SyscallReturn::Return(0));
+    }
+
+    let other_threads = || {
+        tasks
+            .as_slice()
+            .iter()
+            .filter_map(|task| task.as_posix_thread())
+            .filter(|posix_thread| !core::ptr::eq(*posix_thread, ctx.posix_thread))
+    };
+
+    // A thread can be synchronized only if its filters are a prefix of the new filter chain.
+    for posix_thread in other_threads() {
+        let other_seccomp = posix_thread.seccomp().lock();
+        let can_sync = match other_seccomp.mode() {
+            SeccompMode::Disabled => true,
+            SeccompMode::Strict => false,
+            SeccompMode::Filter => other_seccomp.filter().unwrap().is_in_chain_of(&filter),
+        };
+        if !can_sync {
+            if flags.contains(FilterFlags::TSYNC_ESRCH) {
+                return_errno_with_message!(Errno::ESRCH, "a thread cannot be synchronized");
+            }
+            return Ok(SyscallReturn::Return(posix_thread.ns_tid() as _));
+        }
+    }
+
+    seccomp.set_filter(filter.clone())?;
+    let no_new_privs = ctx.posix_thread.no_new_privs();
+    for posix_thread in other_threads() {
+        posix_thread.seccomp().lock().set_filter(filter.clone())?;
+        if no_new_privs {
+            posix_thread.set_no_new_privs();
+        }
+    }
+
+    Ok(SyscallReturn::Return(0))
+}
+
+/// The system calls that are allowed in the strict mode.
+//
+// TODO: Allow `rt_sigreturn` on RISC-V once it is supported.
+const STRICT_MODE_SYSCALLS: &[u64] = &[
+    SYS_READ,
+    SYS_WRITE,
+    SYS_EXIT,
+    #[cfg(target_arch = "x86_64")]
+    SYS_RT_SIGRETURN,
+];
+
+/// The 32-bit system calls that are allowed in the strict mode.
+//
+// TODO: Allow `sigreturn` once the 32-bit signal frames are supported.
+#[cfg(target_arch = "x86_64")]
+const COMPAT_STRICT_MODE_SYSCALLS: &[u64] =
+    &[compat::SYS_READ, compat::SYS_WRITE, compat::SYS_EXIT];
+
+/// The maximum error number that a filter can return.
+const MAX_ERRNO: u16 = 4095;
+
+/// Checks the system call against the seccomp state of the current thread.
+///
+/// This method returns `None` if the system call should be made. Otherwise, the system call is
+/// skipped, and this method returns the result that should be returned to the user space.
+pub(super) fn check_syscall(
+    ctx: &Context,
+    user_ctx: &UserContext,
+    arch: u32,
+    syscall_number: u64,
+    args: [u64; 6],
+) -> Option<Result<SyscallReturn>> {
+    let filter = {
+        let seccomp = ctx.posix_thread.seccomp().lock();
+        match seccomp.mode() {
+            SeccompMode::Disabled => return None,
+            SeccompMode::Strict => {
+                #[cfg(target_arch = "x86_64")]
+                let strict_mode_syscalls = if user_ctx.is_compat_syscall() {
+                    COMPAT_STRICT_MODE_SYSCALLS
+                } else {
+                    STRICT_MODE_SYSCALLS
+                };
+                #[cfg(not(target_arch = "x86_64"))]
+                let strict_mode_syscalls = STRICT_MODE_SYSCALLS;
+                if strict_mode_syscalls.contains(&syscall_number) {
+                    return None;
+                }
+                drop(seccomp);
+                do_exit(TermStatus::Killed(SIGKILL));
+                return Some(Ok(SyscallReturn::NoReturn));
+            }
+            SeccompMode::Filter => seccomp.filter().unwrap().clone(),
+        }
+    };
+
+    let data = SeccompData {
+        nr: syscall_number as i32,
+        arch,
+        instruction_pointer: user_ctx.instruction_pointer() as u64,
+        args,
+    };
+
+    let action = SeccompAction::from_ret(filter.run(&data));
+    match action {
+        SeccompAction::Allow => None,
+        SeccompAction::Log => {
+            info!("seccomp: logged syscall {}", syscall_number);
+            None
+        }
+        SeccompAction::Errno(errno) => {
+            let errno = errno.min(MAX_ERRNO);
+            Some(Ok(SyscallReturn::Return(-(errno as isize))))
+        }
+        SeccompAction::Trap(errno) => {
+            let signal = SeccompSignal::new(
+                data.instruction_pointer as Vaddr,
+                data.nr,
+                data.arch,
+                errno as i32,
+            );
+            force_sigsys(ctx, signal);
+            // The registers are left untouched for the signal handler to inspect.
+            Some(Ok(SyscallReturn::NoReturn))
+        }
+        // TODO: Notify the tracer once `PTRACE_O_TRACESECCOMP` is supported.
+        SeccompAction::Trace | SeccompAction::UserNotif => Some(Err(Error::with_message(
+            Errno::ENOSYS,
+            "the system call is not handled by a tracer or supervisor",
+        ))),
+        SeccompAction::KillThread => {
+            do_exit(TermStatus::Killed(SIGSYS));
+            Some(Ok(SyscallReturn::NoReturn))
+        }
+        SeccompAction::KillProcess => {
+            do_exit_group(TermStatus::Killed(SIGSYS));
+            Some(Ok(SyscallReturn::NoReturn))
+        }
+    }
+}
+
+/// Sends the `SIGSYS` signal to the current thread, which cannot be blocked or ignored.
+///
+/// Similar to Linux, if the signal is blocked or ignored, it is unblocked and its action is
+/// reset to the default one, which terminates the process.
+fn force_sigsys(ctx: &Context, signal: SeccompSignal) {
+    let sig_mask = ctx.posix_thread.sig_mask();
+    let is_blocked = sig_mask.contains(SIGSYS, Ordering::Relaxed);
+
+    let mut sig_dispositions = ctx.process.sig_dispositions().lock();
+    if is_blocked || sig_dispositions.get(SIGSYS) == SigAction::Ign {
+        sig_dispositions.set_default(SIGSYS);
+    }
+    if is_blocked {
+        sig_mask.store(sig_mask.load(Ordering::Relaxed) - SIGSYS, Ordering::Relaxed);
+    }
+    drop(sig_dispositions);
+
+    ctx.posix_thread.enqueue_signal(Box::new(signal));
+}
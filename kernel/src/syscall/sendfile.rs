@@ -2,7 +2,10 @@
 
 use super::SyscallReturn;
 use crate::{
-    fs::file_table::{FileDesc, WithFileTable},
+    fs::{
+        file_table::{FileDesc, WithFileTable},
+        splice::{splice, SpliceEnd},
+    },
     prelude::*,
 };
 
@@ -53,66 +56,22 @@ pub fn sys_sendfile(
         count = MAX_COUNT;
     }
 
-    const BUFFER_SIZE: usize = PAGE_SIZE;
-    let mut buffer = vec![0u8; BUFFER_SIZE].into_boxed_slice();
-    let mut total_len = 0;
+    // The offset decides how to read from `in_file`.
+    // If offset is `Some(_)`, the data will be read from the given offset,
+    // and after reading, the file offset of `in_file` will remain unchanged.
+    // If offset is `None`, the data will be read from the file offset,
+    // and the file offset of `in_file` is adjusted
+    // to reflect the number of bytes read from `in_file`.
     let mut offset = offset.map(|offset| offset as usize);
 
-    while total_len < count {
-        // The offset decides how to read from `in_file`.
-        // If offset is `Some(_)`, the data will be read from the given offset,
-        // and after reading, the file offset of `in_file` will remain unchanged.
-        // If offset is `None`, the data will be read from the file offset,
-        // and the file offset of `in_file` is adjusted
-        // to reflect the number of bytes read from `in_file`.
-        let max_readlen = buffer.len().min(count - total_len);
-
-        // Read from `in_file`
-        let read_res = if let Some(offset) = offset.as_mut() {
-            let res = in_file.read_bytes_at(*offset, &mut buffer[..max_readlen]);
-            if let Ok(len) = res.as_ref() {
-                *offset += *len;
-            }
-            res
-        } else {
-            in_file.read_bytes(&mut buffer[..max_readlen])
-        };
-
-        let read_len = match read_res {
-            Ok(len) => len,
-            Err(e) => {
-                if total_len > 0 {
-                    warn!("error occurs when trying to read file: {:?}", e);
-                    break;
-                }
-                return Err(e);
-            }
-        };
-
-        if read_len == 0 {
-            break;
-        }
-
-        // Note: `sendfile` allows sending partial data,
-        // so short reads and short writes are all acceptable
-        let write_res = out_file.write_bytes(&buffer[..read_len]);
-
-        match write_res {
-            Ok(len) => {
-                total_len += len;
-                if len < BUFFER_SIZE {
-                    break;
-                }
-            }
-            Err(e) => {
-                if total_len > 0 {
-                    warn!("error occurs when trying to write file: {:?}", e);
-                    break;
-                }
-                return Err(e);
-            }
-        }
-    }
+    // Note: `sendfile` allows sending partial data,
+    // so short reads and short writes are all acceptable
+    let total_len = splice(
+        SpliceEnd::new(&in_file, offset.as_mut()),
+        SpliceEnd::new(&out_file, None),
+        count,
+        false,
+    )?;
 
     if let Some(offset) = offset {
         ctx.user_space().write_val(offset_ptr, &(offset as isize))?;
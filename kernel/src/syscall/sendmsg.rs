@@ -22,25 +22,21 @@ pub fn sys_sendmsg(
         sockfd, c_user_msghdr, flags
     );
 
-    let mut file_table = ctx.thread_local.file_table().borrow_mut();
-    let file = get_file_fast!(&mut file_table, sockfd);
-    let socket = file.as_socket_or_err()?;
-
+    let user_space = ctx.user_space();
+    // The control messages are read before borrowing the file table mutably, since the file
+    // descriptors in them are resolved with the file table.
     let (mut io_vec_reader, message_header) = {
         let addr = c_user_msghdr.read_socket_addr_from_user()?;
-        let io_vec_reader = c_user_msghdr.copy_reader_array_from_user(ctx)?;
-
-        let control_message = {
-            if c_user_msghdr.msg_control != 0 {
-                // TODO: support sending control message
-                warn!("control message is not supported now");
-            }
-            None
-        };
+        let io_vec_reader = c_user_msghdr.copy_reader_array_from_user(&user_space)?;
+        let control_messages = c_user_msghdr.read_control_messages_from_user(ctx)?;
 
-        (io_vec_reader, MessageHeader::new(addr, control_message))
+        (io_vec_reader, MessageHeader::new(addr, control_messages))
     };
 
+    let mut file_table = ctx.thread_local.file_table().borrow_mut();
+    let file = get_file_fast!(&mut file_table, sockfd);
+    let socket = file.as_socket_or_err()?;
+
     let total_bytes = socket
         .sendmsg(&mut io_vec_reader, message_header, flags)
         .map_err(|err| match err.error() {
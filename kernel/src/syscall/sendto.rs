@@ -30,12 +30,10 @@ pub fn sys_sendto(
     let file = get_file_fast!(&mut file_table, sockfd);
     let socket = file.as_socket_or_err()?;
 
-    let message_header = MessageHeader::new(socket_addr, None);
+    let message_header = MessageHeader::new(socket_addr, Vec::new());
 
-    let mut reader = {
-        let vm_space = ctx.process.root_vmar().vm_space();
-        vm_space.reader(buf, len)?
-    };
+    let user_space = ctx.user_space();
+    let mut reader = user_space.reader(buf, len)?;
     let send_size = socket
         .sendmsg(&mut reader, message_header, flags)
         .map_err(|err| match err.error() {
@@ -5,8 +5,15 @@ use core::sync::atomic::Ordering;
 use super::SyscallReturn;
 use crate::{
     prelude::*,
-    process::{posix_thread::AsPosixThread, process_table, Pgid, Pid, Process, Uid},
-    sched::priority::{Nice, NiceRange},
+    process::{
+        credentials::capabilities::CapSet, posix_thread::AsPosixThread, process_table, Pgid, Pid,
+        Process, ResourceType, Uid,
+    },
+    sched::{
+        priority::{Nice, NiceRange},
+        SchedPolicy,
+    },
+    thread::AsThread,
 };
 
 pub fn sys_set_priority(which: i32, who: u32, prio: i32, ctx: &Context) -> Result<SyscallReturn> {
@@ -21,10 +28,27 @@ pub fn sys_set_priority(which: i32, who: u32, prio: i32, ctx: &Context) -> Resul
         prio_target, new_nice
     );
 
+    // Like Linux, the nice values of the permitted processes are changed even if some of the
+    // processes are not permitted, in which case an error is still returned.
+    let mut result = Ok(());
     let processes = get_processes(prio_target)?;
     for process in processes.iter() {
+        if let Err(err) = check_nice_perm(process, new_nice, ctx) {
+            result = Err(err);
+            continue;
+        }
+
         process.nice().store(new_nice, Ordering::Relaxed);
+
+        // The nice value only affects the threads with the fair scheduling policy.
+        for task in process.tasks().lock().as_slice() {
+            let sched_attr = task.as_thread().unwrap().sched_attr();
+            if let SchedPolicy::Fair(_) = sched_attr.policy() {
+                sched_attr.set_policy(SchedPolicy::Fair(new_nice));
+            }
+        }
     }
+    result?;
 
     Ok(SyscallReturn::Return(0))
 }
@@ -52,6 +76,47 @@ pub fn sys_get_priority(which: i32, who: u32, ctx: &Context) -> Result<SyscallRe
     Ok(SyscallReturn::Return(highest_prio as _))
 }
 
+/// Checks whether the current thread can set the nice value of the target process.
+///
+/// Like Linux, a thread without `CAP_SYS_NICE` can only change the nice values of the processes
+/// that are owned by the same user, and can only decrease the nice values within `RLIMIT_NICE`.
+fn check_nice_perm(target: &Process, new_nice: Nice, ctx: &Context) -> Result<()> {
+    let credentials = ctx.posix_thread.credentials();
+    if credentials.effective_capset().contains(CapSet::SYS_NICE) {
+        return Ok(());
+    }
+
+    let target_credentials = target
+        .main_thread()
+        .as_posix_thread()
+        .unwrap()
+        .credentials();
+    let euid = credentials.euid();
+    if euid != target_credentials.ruid() && euid != target_credentials.euid() {
+        return_errno_with_message!(Errno::EPERM, "the target process is owned by another user");
+    }
+
+    let new_nice = new_nice.range().get();
+    let old_nice = target.nice().load(Ordering::Relaxed).range().get();
+    if new_nice < old_nice {
+        // The limit is in the range of 1 to 40, which is `20 - nice`.
+        let limit = ctx
+            .process
+            .resource_limits()
+            .lock()
+            .get_rlimit(ResourceType::RLIMIT_NICE)
+            .get_cur();
+        if (20 - new_nice as i64) as u64 > limit {
+            return_errno_with_message!(
+                Errno::EACCES,
+                "decreasing the nice value requires CAP_SYS_NICE"
+            );
+        }
+    }
+
+    Ok(())
+}
+
 fn get_processes(prio_target: PriorityTarget) -> Result<Vec<Arc<Process>>> {
     Ok(match prio_target {
         PriorityTarget::Process(pid) => {
@@ -102,7 +167,7 @@ impl PriorityTarget {
                 let pid = if who == 0 {
                     ctx.process.pid()
                 } else {
-                    who as Pid
+                    ctx.process.pid_ns().global_id(who).unwrap_or(0)
                 };
                 Self::Process(pid)
             }
@@ -110,7 +175,7 @@ impl PriorityTarget {
                 let pgid = if who == 0 {
                     ctx.process.pgid()
                 } else {
-                    who as Pgid
+                    ctx.process.pid_ns().global_id(who).unwrap_or(0)
                 };
                 Self::ProcessGroup(pgid)
             }
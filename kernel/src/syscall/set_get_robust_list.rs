@@ -0,0 +1,69 @@
+// SPDX-License-Identifier: MPL-2.0
+
+use super::SyscallReturn;
+use crate::{
+    prelude::*,
+    process::posix_thread::{thread_table, RobustListHead},
+    thread::Tid,
+};
+
+pub fn sys_set_robust_list(
+    robust_list_head_ptr: Vaddr,
+    len: usize,
+    ctx: &Context,
+) -> Result<SyscallReturn> {
+    debug!(
+        "robust list head ptr: 0x{:x}, len = {}",
+        robust_list_head_ptr, len
+    );
+
+    if len != core::mem::size_of::<RobustListHead>() {
+        return_errno_with_message!(
+            Errno::EINVAL,
+            "the length is not equal to the size of the robust list head"
+        );
+    }
+
+    // Like Linux, the robust list head is not read until the thread exits.
+    ctx.thread_local.robust_list().set(robust_list_head_ptr);
+
+    Ok(SyscallReturn::Return(0))
+}
+
+pub fn sys_get_robust_list(
+    tid: Tid,
+    robust_list_head_ptr_ptr: Vaddr,
+    len_ptr: Vaddr,
+    ctx: &Context,
+) -> Result<SyscallReturn> {
+    debug!(
+        "tid = {}, robust list head ptr ptr: 0x{:x}, len ptr: 0x{:x}",
+        tid, robust_list_head_ptr_ptr, len_ptr
+    );
+
+    // TODO: Support getting the robust lists of other threads. They are recorded in the
+    // thread-local data, so they cannot be accessed by the current thread.
+    if tid != 0 && tid != ctx.posix_thread.ns_tid() {
+        if ctx
+            .process
+            .pid_ns()
+            .global_id(tid)
+            .and_then(thread_table::get_thread)
+            .is_none()
+        {
+            return_errno_with_message!(Errno::ESRCH, "the thread does not exist");
+        }
+        return_errno_with_message!(
+            Errno::EPERM,
+            "getting the robust lists of other threads is not supported"
+        );
+    }
+
+    let robust_list_head_ptr = ctx.thread_local.robust_list().get();
+
+    let user_space = ctx.user_space();
+    user_space.write_val(len_ptr, &core::mem::size_of::<RobustListHead>())?;
+    user_space.write_val(robust_list_head_ptr_ptr, &robust_list_head_ptr)?;
+
+    Ok(SyscallReturn::Return(0))
+}
@@ -1,12 +1,22 @@
 // SPDX-License-Identifier: MPL-2.0
 
 use super::SyscallReturn;
-use crate::{prelude::*, process::Gid};
+use crate::{
+    prelude::*,
+    process::{credentials::capabilities::CapSet, Gid},
+};
 
 pub fn sys_setgroups(size: usize, group_list_addr: Vaddr, ctx: &Context) -> Result<SyscallReturn> {
     debug!("size = {}, group_list_addr = 0x{:x}", size, group_list_addr);
 
-    // TODO: check perm: the calling process should have the CAP_SETGID capability
+    if !ctx
+        .posix_thread
+        .credentials()
+        .effective_capset()
+        .contains(CapSet::SETGID)
+    {
+        return_errno_with_message!(Errno::EPERM, "setting the groups requires CAP_SETGID");
+    }
 
     if size > NGROUPS_MAX {
         return_errno_with_message!(Errno::EINVAL, "size cannot be greater than NGROUPS_MAX");
@@ -0,0 +1,47 @@
+// SPDX-License-Identifier: MPL-2.0
+
+use super::SyscallReturn;
+use crate::{
+    prelude::*,
+    process::{credentials::capabilities::CapSet, namespace::UtsNamespace},
+};
+
+pub fn sys_sethostname(name_ptr: Vaddr, len: usize, ctx: &Context) -> Result<SyscallReturn> {
+    debug!("name_ptr = {:#x}, len = {}", name_ptr, len);
+
+    let name = read_name(name_ptr, len, ctx)?;
+    let ns_proxy = ctx.posix_thread.ns_proxy().lock().clone();
+    ns_proxy.uts_ns().set_hostname(&name)?;
+
+    Ok(SyscallReturn::Return(0))
+}
+
+pub fn sys_setdomainname(name_ptr: Vaddr, len: usize, ctx: &Context) -> Result<SyscallReturn> {
+    debug!("name_ptr = {:#x}, len = {}", name_ptr, len);
+
+    let name = read_name(name_ptr, len, ctx)?;
+    let ns_proxy = ctx.posix_thread.ns_proxy().lock().clone();
+    ns_proxy.uts_ns().set_domainname(&name)?;
+
+    Ok(SyscallReturn::Return(0))
+}
+
+fn read_name(name_ptr: Vaddr, len: usize, ctx: &Context) -> Result<Vec<u8>> {
+    if !ctx
+        .posix_thread
+        .credentials()
+        .effective_capset()
+        .contains(CapSet::SYS_ADMIN)
+    {
+        return_errno_with_message!(Errno::EPERM, "changing the name requires CAP_SYS_ADMIN");
+    }
+
+    if len > UtsNamespace::MAX_NAME_LEN {
+        return_errno_with_message!(Errno::EINVAL, "the name is too long");
+    }
+
+    let mut name = vec![0u8; len];
+    ctx.user_space()
+        .read_bytes(name_ptr, &mut VmWriter::from(name.as_mut_slice()))?;
+    Ok(name)
+}
@@ -0,0 +1,66 @@
+// SPDX-License-Identifier: MPL-2.0
+
+use super::SyscallReturn;
+use crate::{
+    fs::{
+        file_table::{get_file_fast, FileDesc},
+        procfs,
+    },
+    prelude::*,
+    process::{credentials::capabilities::CapSet, namespace::Namespace, CloneFlags},
+};
+
+pub fn sys_setns(fd: FileDesc, nstype: i32, ctx: &Context) -> Result<SyscallReturn> {
+    debug!("fd = {}, nstype = {:#x}", fd, nstype);
+
+    let ns = {
+        let mut file_table = ctx.thread_local.file_table().borrow_mut();
+        let file = get_file_fast!(&mut file_table, fd);
+        let inode = file.as_inode_or_err()?.dentry().inode().clone();
+        procfs::namespace_of(inode.as_ref()).ok_or_else(|| {
+            Error::with_message(Errno::EINVAL, "the file does not refer to a namespace")
+        })?
+    };
+
+    if nstype != 0 && CloneFlags::from(nstype as u32 as u64) != ns.clone_flag() {
+        return_errno_with_message!(
+            Errno::EINVAL,
+            "the namespace does not match the namespace type"
+        );
+    }
+
+    if !ctx
+        .posix_thread
+        .credentials()
+        .effective_capset()
+        .contains(CapSet::SYS_ADMIN)
+    {
+        return_errno_with_message!(Errno::EPERM, "entering a namespace requires CAP_SYS_ADMIN");
+    }
+
+    match &ns {
+        // The root and the current working directory are moved to the new mount tree.
+        Namespace::Mnt(_) if Arc::strong_count(&ctx.thread_local.borrow_fs()) > 1 => {
+            return_errno_with_message!(
+                Errno::EINVAL,
+                "the FS information is shared with other threads"
+            );
+        }
+        // Only the current PID namespace or its descendants can be entered.
+        Namespace::Pid(pid_ns) if !ctx.process.pid_ns().is_ancestor_of(pid_ns) => {
+            return_errno_with_message!(
+                Errno::EINVAL,
+                "the PID namespace is not a descendant of the current one"
+            );
+        }
+        _ => (),
+    }
+
+    let mut ns_proxy = ctx.posix_thread.ns_proxy().lock();
+    let fs_ref = ctx.thread_local.borrow_fs();
+    let mut resolver = fs_ref.resolver().write();
+    let new_ns_proxy = ns_proxy.enter(ns, &mut resolver);
+    *ns_proxy = new_ns_proxy;
+
+    Ok(SyscallReturn::Return(0))
+}
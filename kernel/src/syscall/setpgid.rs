@@ -7,11 +7,20 @@ use crate::{
 };
 
 pub fn sys_setpgid(pid: Pid, pgid: Pgid, ctx: &Context) -> Result<SyscallReturn> {
+    if (pgid as i32) < 0 {
+        return_errno_with_message!(Errno::EINVAL, "negative pgid");
+    }
+
     let current = ctx.process;
+    let to_global_id = |id| current.pid_ns().global_id(id).unwrap_or(0);
     // if pid is 0, pid should be the pid of current process
-    let pid = if pid == 0 { current.pid() } else { pid };
+    let pid = if pid == 0 {
+        current.pid()
+    } else {
+        to_global_id(pid)
+    };
     // if pgid is 0, pgid should be pid
-    let pgid = if pgid == 0 { pid } else { pgid };
+    let pgid = if pgid == 0 { pid } else { to_global_id(pgid) };
     debug!("pid = {}, pgid = {}", pid, pgid);
 
     if pid != current.pid() && !current.has_child(&pid) {
@@ -20,8 +29,6 @@ pub fn sys_setpgid(pid: Pid, pgid: Pgid, ctx: &Context) -> Result<SyscallReturn>
             "cannot set pgid for process other than current or children of current"
         );
     }
-    // FIXME: If pid is child process of current and already calls execve, should return error.
-    // How can we determine a child process has called execve?
 
     // only can move process to an existing group or self
     if pgid != pid && !process_table::contain_process_group(&pgid) {
@@ -31,6 +38,23 @@ pub fn sys_setpgid(pid: Pid, pgid: Pgid, ctx: &Context) -> Result<SyscallReturn>
     let process = process_table::get_process(pid)
         .ok_or(Error::with_message(Errno::ESRCH, "process does not exist"))?;
 
+    if pid != current.pid() {
+        let is_same_session = match (process.session(), current.session()) {
+            (Some(session), Some(current_session)) => Arc::ptr_eq(&session, &current_session),
+            _ => false,
+        };
+        if !is_same_session {
+            return_errno_with_message!(
+                Errno::EPERM,
+                "the child process belongs to a different session"
+            );
+        }
+
+        if process.has_called_execve() {
+            return_errno_with_message!(Errno::EACCES, "the child process has called execve");
+        }
+    }
+
     process.to_other_group(pgid)?;
 
     Ok(SyscallReturn::Return(0))
@@ -7,5 +7,6 @@ pub fn sys_setsid(_ctx: &Context) -> Result<SyscallReturn> {
     let current = current!();
     let session = current.to_new_session()?;
 
-    Ok(SyscallReturn::Return(session.sid() as _))
+    let sid = current.pid_ns().local_id(session.sid()).unwrap_or(0);
+    Ok(SyscallReturn::Return(sid as _))
 }
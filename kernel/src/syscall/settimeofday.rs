@@ -0,0 +1,31 @@
+// SPDX-License-Identifier: MPL-2.0
+
+use core::time::Duration;
+
+use super::{clock_settime::check_sys_time_capability, SyscallReturn};
+use crate::{
+    prelude::*,
+    time::{timekeeping, timeval_t},
+};
+
+// The use of the timezone structure is obsolete, so the timezone is ignored.
+pub fn sys_settimeofday(
+    timeval_addr: Vaddr,
+    /* timezone_addr: Vaddr, */ ctx: &Context,
+) -> Result<SyscallReturn> {
+    if timeval_addr == 0 {
+        return Ok(SyscallReturn::Return(0));
+    }
+
+    let timeval = ctx.user_space().read_val::<timeval_t>(timeval_addr)?;
+    debug!("timeval = {:?}", timeval);
+    if timeval.usec >= 1_000_000 {
+        return_errno_with_message!(Errno::EINVAL, "the microseconds are not normalized");
+    }
+    let time = Duration::try_from(timeval)?;
+
+    check_sys_time_capability(ctx)?;
+    timekeeping::set_real_time(time)?;
+
+    Ok(SyscallReturn::Return(0))
+}
@@ -0,0 +1,17 @@
+// SPDX-License-Identifier: MPL-2.0
+
+use super::SyscallReturn;
+use crate::{ipc::shm::ShmAtFlags, prelude::*};
+
+pub fn sys_shmat(shmid: i32, shmaddr: Vaddr, shmflg: i32, ctx: &Context) -> Result<SyscallReturn> {
+    let flags = ShmAtFlags::from_bits_truncate(shmflg as u32);
+    debug!(
+        "[sys_shmat] shmid = {}, shmaddr = {:#x}, flags = {:?}",
+        shmid, shmaddr, flags
+    );
+
+    let ipc_ns = ctx.posix_thread.ns_proxy().lock().ipc_ns().clone();
+    let addr = ipc_ns.shm_segments().attach(shmid, shmaddr, flags, ctx)?;
+
+    Ok(SyscallReturn::Return(addr as isize))
+}
@@ -0,0 +1,30 @@
+// SPDX-License-Identifier: MPL-2.0
+
+use super::SyscallReturn;
+use crate::{ipc::IpcControlCmd, prelude::*};
+
+pub fn sys_shmctl(shmid: i32, cmd: i32, buf: Vaddr, ctx: &Context) -> Result<SyscallReturn> {
+    if shmid < 0 {
+        return_errno_with_message!(Errno::EINVAL, "the segment ID is invalid");
+    }
+
+    let cmd = IpcControlCmd::try_from(cmd)?;
+    debug!(
+        "[sys_shmctl] shmid = {}, cmd = {:?}, buf = {:#x}",
+        shmid, cmd, buf
+    );
+
+    let ipc_ns = ctx.posix_thread.ns_proxy().lock().ipc_ns().clone();
+    let shm_segments = ipc_ns.shm_segments();
+
+    match cmd {
+        IpcControlCmd::IPC_RMID => shm_segments.remove(shmid, ctx)?,
+        IpcControlCmd::IPC_STAT => {
+            let stat = shm_segments.stat(shmid, ctx)?;
+            ctx.user_space().write_val(buf, &stat)?;
+        }
+        _ => return_errno_with_message!(Errno::EINVAL, "the command is not supported"),
+    }
+
+    Ok(SyscallReturn::Return(0))
+}
@@ -0,0 +1,13 @@
+// SPDX-License-Identifier: MPL-2.0
+
+use super::SyscallReturn;
+use crate::prelude::*;
+
+pub fn sys_shmdt(shmaddr: Vaddr, ctx: &Context) -> Result<SyscallReturn> {
+    debug!("[sys_shmdt] shmaddr = {:#x}", shmaddr);
+
+    let ipc_ns = ctx.posix_thread.ns_proxy().lock().ipc_ns().clone();
+    ipc_ns.shm_segments().detach(shmaddr, ctx)?;
+
+    Ok(SyscallReturn::Return(0))
+}
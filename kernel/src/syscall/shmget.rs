@@ -0,0 +1,18 @@
+// SPDX-License-Identifier: MPL-2.0
+
+use super::SyscallReturn;
+use crate::prelude::*;
+
+pub fn sys_shmget(key: i32, size: usize, shmflg: i32, ctx: &Context) -> Result<SyscallReturn> {
+    debug!(
+        "[sys_shmget] key = {}, size = {}, shmflg = {:#o}",
+        key, size, shmflg
+    );
+
+    let ipc_ns = ctx.posix_thread.ns_proxy().lock().ipc_ns().clone();
+    let shm_id = ipc_ns
+        .shm_segments()
+        .get_or_create(key, size, shmflg, ctx)?;
+
+    Ok(SyscallReturn::Return(shm_id as isize))
+}
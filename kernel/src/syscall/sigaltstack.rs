@@ -1,132 +1,70 @@
 // SPDX-License-Identifier: MPL-2.0
 
+use ostd::{cpu::UserContext, user::UserContextApi};
+
 use super::SyscallReturn;
 use crate::{
     prelude::*,
-    process::signal::{SigStack, SigStackFlags},
+    process::signal::{c_types::stack_t, SigStack},
 };
 
 pub fn sys_sigaltstack(
     sig_stack_addr: Vaddr,
     old_sig_stack_addr: Vaddr,
     ctx: &Context,
+    user_ctx: &UserContext,
 ) -> Result<SyscallReturn> {
     debug!(
         "sig_stack_addr = 0x{:x}, old_sig_stack_addr: 0x{:x}",
         sig_stack_addr, old_sig_stack_addr
     );
 
+    let sp = user_ctx.stack_pointer();
     let old_stack = ctx.thread_local.sig_stack().borrow().clone();
 
-    get_old_stack(old_sig_stack_addr, old_stack.as_ref(), ctx)?;
-    set_new_stack(sig_stack_addr, old_stack.as_ref(), ctx)?;
+    get_old_stack(old_sig_stack_addr, &old_stack, sp, ctx)?;
+    set_new_stack(sig_stack_addr, &old_stack, sp, ctx)?;
 
     Ok(SyscallReturn::Return(0))
 }
 
 fn get_old_stack(
     old_sig_stack_addr: Vaddr,
-    old_stack: Option<&SigStack>,
+    old_stack: &SigStack,
+    sp: Vaddr,
     ctx: &Context,
 ) -> Result<()> {
     if old_sig_stack_addr == 0 {
         return Ok(());
     }
 
-    if let Some(old_stack) = old_stack {
-        debug!("old stack = {:?}", old_stack);
+    debug!("old stack = {:?}", old_stack);
 
-        let stack = stack_t::from(old_stack.clone());
-        ctx.user_space()
-            .write_val::<stack_t>(old_sig_stack_addr, &stack)?;
-    } else {
-        let stack = stack_t {
-            sp: 0,
-            flags: SigStackFlags::SS_DISABLE.bits() as i32,
-            size: 0,
-        };
-        ctx.user_space()
-            .write_val::<stack_t>(old_sig_stack_addr, &stack)?;
-    }
+    ctx.user_space()
+        .write_val::<stack_t>(old_sig_stack_addr, &old_stack.as_c_type(sp))?;
 
     Ok(())
 }
 
-fn set_new_stack(sig_stack_addr: Vaddr, old_stack: Option<&SigStack>, ctx: &Context) -> Result<()> {
+fn set_new_stack(
+    sig_stack_addr: Vaddr,
+    old_stack: &SigStack,
+    sp: Vaddr,
+    ctx: &Context,
+) -> Result<()> {
     if sig_stack_addr == 0 {
         return Ok(());
     }
 
-    if let Some(old_stack) = old_stack
-        && old_stack.is_active()
-    {
+    let stack = ctx.user_space().read_val::<stack_t>(sig_stack_addr)?;
+    if old_stack.is_on_stack(sp) {
         return_errno_with_message!(Errno::EPERM, "the old stack is active now");
     }
-
-    let new_stack = {
-        let stack = ctx.user_space().read_val::<stack_t>(sig_stack_addr)?;
-        SigStack::try_from(stack)?
-    };
+    let new_stack = SigStack::try_from(stack)?;
 
     debug!("new_stack = {:?}", new_stack);
 
-    *ctx.thread_local.sig_stack().borrow_mut() = Some(new_stack);
+    *ctx.thread_local.sig_stack().borrow_mut() = new_stack;
 
     Ok(())
 }
-
-#[allow(non_camel_case_types)]
-#[derive(Debug, Clone, Copy, Pod)]
-#[repr(C)]
-struct stack_t {
-    // Base address of stack
-    sp: Vaddr,
-    flags: i32,
-    // Number of bytes in stack
-    size: usize,
-}
-
-impl TryFrom<stack_t> for SigStack {
-    type Error = Error;
-
-    fn try_from(stack: stack_t) -> Result<Self> {
-        if stack.flags < 0 {
-            return_errno_with_message!(Errno::EINVAL, "negative flags");
-        }
-
-        let mut flags = SigStackFlags::from_bits(stack.flags as u32)
-            .ok_or_else(|| Error::with_message(Errno::EINVAL, "invalid flags"))?;
-
-        if flags.contains(SigStackFlags::SS_DISABLE) {
-            return Ok(Self::new(0, flags, 0));
-        }
-        if stack.size < MINSTKSZ {
-            return_errno_with_message!(Errno::ENOMEM, "stack size is less than MINSTKSZ");
-        }
-        if stack.sp.checked_add(stack.size).is_none() {
-            return_errno_with_message!(Errno::EINVAL, "overflow for given stack addr and size");
-        }
-
-        if flags.is_empty() {
-            flags.insert(SigStackFlags::SS_ONSTACK);
-        }
-
-        Ok(Self::new(stack.sp, flags, stack.size))
-    }
-}
-
-impl From<SigStack> for stack_t {
-    fn from(stack: SigStack) -> Self {
-        let flags = stack.flags().bits() as i32 | stack.status() as i32;
-
-        Self {
-            sp: stack.base(),
-            flags,
-            size: stack.size(),
-        }
-    }
-}
-
-#[allow(unused)]
-const SIGSTKSZ: usize = 8192;
-const MINSTKSZ: usize = 2048;
@@ -0,0 +1,341 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! `signalfd()` creates a file descriptor (we name it as `SignalFile`) that can be used to
+//! accept signals targeted at the caller.
+//!
+//! Reading from `SignalFile` dequeues the pending signals in the mask of the file, and returns
+//! their information as `signalfd_siginfo` structures. To prevent the signals from being handled
+//! according to their dispositions, the signals should be blocked with `sigprocmask()`.
+//!
+//! For more detailed information about this syscall,
+//! refer to the man 2 signalfd documentation.
+
+use core::sync::atomic::Ordering;
+
+use super::SyscallReturn;
+use crate::{
+    events::{IoEvents, Observer},
+    fs::{
+        file_handle::FileLike,
+        file_table::{get_file_fast, max_fds, FdFlags, FileDesc},
+        utils::{CreationFlags, InodeMode, InodeType, Metadata, StatusFlags},
+    },
+    prelude::*,
+    process::{
+        posix_thread::AsPosixThread,
+        signal::{
+            c_types::siginfo_t,
+            constants::{SIGKILL, SIGSTOP, SI_KERNEL, SI_QUEUE, SI_TKILL, SI_USER},
+            sig_mask::{AtomicSigMask, SigMask},
+            PollHandle, Pollable, Pollee, SigEvents, SigEventsFilter,
+        },
+        Gid, Process, Uid,
+    },
+    thread::Thread,
+    time::clocks::RealTimeClock,
+};
+
+pub fn sys_signalfd(
+    fd: FileDesc,
+    mask_ptr: Vaddr,
+    sizemask: usize,
+    ctx: &Context,
+) -> Result<SyscallReturn> {
+    sys_signalfd4(fd, mask_ptr, sizemask, 0, ctx)
+}
+
+pub fn sys_signalfd4(
+    fd: FileDesc,
+    mask_ptr: Vaddr,
+    sizemask: usize,
+    flags: u32,
+    ctx: &Context,
+) -> Result<SyscallReturn> {
+    let flags = Flags::from_bits(flags)
+        .ok_or_else(|| Error::with_message(Errno::EINVAL, "unknown flags"))?;
+    debug!(
+        "fd = {}, mask_ptr = 0x{:x}, sizemask = {}, flags = {:?}",
+        fd, mask_ptr, sizemask, flags
+    );
+
+    if sizemask != core::mem::size_of::<SigMask>() {
+        return_errno_with_message!(Errno::EINVAL, "the size of the signal mask is invalid");
+    }
+    let mut mask = ctx.user_space().read_val::<SigMask>(mask_ptr)?;
+    // According to the man pages, "it is not possible to receive SIGKILL or SIGSTOP signals via
+    // a signalfd file descriptor; these signals are silently ignored if specified in mask."
+    mask -= SIGKILL;
+    mask -= SIGSTOP;
+
+    if fd != -1 {
+        let mut file_table = ctx.thread_local.file_table().borrow_mut();
+        let file = get_file_fast!(&mut file_table, fd);
+        let signal_file = file
+            .downcast_ref::<SignalFile>()
+            .ok_or_else(|| Error::with_message(Errno::EINVAL, "the file is not a signal file"))?;
+        signal_file.set_mask(mask);
+        return Ok(SyscallReturn::Return(fd as _));
+    }
+
+    let signal_file = SignalFile::new(mask, flags, ctx);
+    let fd = {
+        let max_fds = max_fds(&ctx.process);
+        let file_table = ctx.thread_local.file_table().borrow();
+        let mut file_table_locked = file_table.write();
+        let fd_flags = if flags.contains(Flags::SFD_CLOEXEC) {
+            FdFlags::CLOEXEC
+        } else {
+            FdFlags::empty()
+        };
+        file_table_locked.insert(signal_file, fd_flags, max_fds)?
+    };
+
+    Ok(SyscallReturn::Return(fd as _))
+}
+
+bitflags! {
+    struct Flags: u32 {
+        const SFD_CLOEXEC = CreationFlags::O_CLOEXEC.bits();
+        const SFD_NONBLOCK = StatusFlags::O_NONBLOCK.bits();
+    }
+}
+
+/// The information of a signal read from `SignalFile`.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Pod)]
+struct SignalfdSiginfo {
+    ssi_signo: u32,
+    ssi_errno: i32,
+    ssi_code: i32,
+    ssi_pid: u32,
+    ssi_uid: u32,
+    ssi_fd: i32,
+    ssi_tid: u32,
+    ssi_band: u32,
+    ssi_overrun: u32,
+    ssi_trapno: u32,
+    ssi_status: i32,
+    ssi_int: i32,
+    ssi_ptr: u64,
+    ssi_utime: u64,
+    ssi_stime: u64,
+    ssi_addr: u64,
+    ssi_addr_lsb: u16,
+    _pad2: u16,
+    ssi_syscall: i32,
+    ssi_call_addr: u64,
+    ssi_arch: u32,
+    _pad: [u8; 28],
+}
+
+impl From<siginfo_t> for SignalfdSiginfo {
+    fn from(info: siginfo_t) -> Self {
+        let mut ssi = Self::new_zeroed();
+        ssi.ssi_signo = info.si_signo as u32;
+        ssi.ssi_errno = info.si_errno;
+        ssi.ssi_code = info.si_code;
+
+        match info.si_code {
+            SI_USER | SI_TKILL | SI_QUEUE => {
+                ssi.ssi_pid = info.si_pid();
+                ssi.ssi_uid = info.si_uid().into();
+            }
+            code if code > 0 && code != SI_KERNEL => ssi.ssi_addr = info.si_addr() as u64,
+            _ => (),
+        }
+
+        ssi
+    }
+}
+
+/// A file that accepts signals.
+///
+/// The file monitors the signal queue of the thread that creates it and the signal queue of its
+/// process, so that it can be polled for the arrival of the thread-directed and the
+/// process-directed signals. The signals are read from the signal queues of the thread that reads
+/// the file and of its process.
+struct SignalFile {
+    mask: AtomicSigMask,
+    pollee: Pollee,
+    flags: Mutex<Flags>,
+    /// The thread whose signal queue is monitored.
+    thread: Weak<Thread>,
+    /// The process whose signal queue is monitored.
+    process: Weak<Process>,
+    weak_self: Weak<SignalFile>,
+}
+
+impl SignalFile {
+    fn new(mask: SigMask, flags: Flags, ctx: &Context) -> Arc<Self> {
+        let thread = current_thread!();
+
+        let signal_file = Arc::new_cyclic(|weak_self| Self {
+            mask: AtomicSigMask::new(mask),
+            pollee: Pollee::new(),
+            flags: Mutex::new(flags),
+            thread: Arc::downgrade(&thread),
+            process: Arc::downgrade(ctx.process),
+            weak_self: weak_self.clone(),
+        });
+
+        ctx.posix_thread
+            .register_sigqueue_observer(signal_file.weak_observer(), filter_of(mask));
+        ctx.process
+            .register_sigqueue_observer(signal_file.weak_observer(), filter_of(mask));
+
+        signal_file
+    }
+
+    fn weak_observer(&self) -> Weak<dyn Observer<SigEvents>> {
+        self.weak_self.clone() as _
+    }
+
+    fn set_mask(&self, mask: SigMask) {
+        self.mask.store(mask, Ordering::Relaxed);
+
+        if let Some(thread) = self.thread.upgrade() {
+            let posix_thread = thread.as_posix_thread().unwrap();
+            posix_thread.register_sigqueue_observer(self.weak_observer(), filter_of(mask));
+        }
+        if let Some(process) = self.process.upgrade() {
+            process.register_sigqueue_observer(self.weak_observer(), filter_of(mask));
+        }
+
+        self.pollee.notify(IoEvents::IN);
+    }
+
+    fn is_nonblocking(&self) -> bool {
+        self.flags.lock().contains(Flags::SFD_NONBLOCK)
+    }
+
+    fn try_read(&self, writer: &mut VmWriter) -> Result<usize> {
+        let current = current_thread!();
+        let posix_thread = current.as_posix_thread().unwrap();
+        let blocked = SigMask::new_full() - self.mask.load(Ordering::Relaxed);
+
+        let ssi_size = core::mem::size_of::<SignalfdSiginfo>();
+        let mut read_len = 0;
+        while writer.avail() >= ssi_size {
+            let Some(signal) = posix_thread.dequeue_signal(&blocked) else {
+                break;
+            };
+            let ssi = SignalfdSiginfo::from(signal.to_info());
+            if let Err(err) = writer.write_val(&ssi) {
+                // Put the signal back.
+                posix_thread.enqueue_signal(signal);
+                if read_len == 0 {
+                    return Err(err.into());
+                }
+                break;
+            }
+            read_len += ssi_size;
+        }
+
+        self.pollee.invalidate();
+
+        if read_len == 0 {
+            return_errno_with_message!(Errno::EAGAIN, "no signals are pending");
+        }
+        Ok(read_len)
+    }
+
+    fn check_io_events(&self) -> IoEvents {
+        let Some(thread) = self.thread.upgrade() else {
+            return IoEvents::empty();
+        };
+        let posix_thread = thread.as_posix_thread().unwrap();
+
+        let pending = posix_thread.sig_pending() & self.mask.load(Ordering::Relaxed);
+        if pending.is_empty() {
+            IoEvents::empty()
+        } else {
+            IoEvents::IN
+        }
+    }
+}
+
+/// Returns the filter of the signal events that are accepted by the mask.
+fn filter_of(mask: SigMask) -> SigEventsFilter {
+    SigEventsFilter::new(SigMask::new_full() - mask)
+}
+
+impl Observer<SigEvents> for SignalFile {
+    fn on_events(&self, _events: &SigEvents) {
+        self.pollee.notify(IoEvents::IN);
+    }
+}
+
+impl Drop for SignalFile {
+    fn drop(&mut self) {
+        if let Some(thread) = self.thread.upgrade() {
+            let posix_thread = thread.as_posix_thread().unwrap();
+            posix_thread.unregister_sigqueue_observer(&self.weak_observer());
+        }
+        if let Some(process) = self.process.upgrade() {
+            process.unregister_sigqueue_observer(&self.weak_observer());
+        }
+    }
+}
+
+impl Pollable for SignalFile {
+    fn poll(&self, mask: IoEvents, poller: Option<&mut PollHandle>) -> IoEvents {
+        self.pollee
+            .poll_with(mask, poller, || self.check_io_events())
+    }
+}
+
+impl FileLike for SignalFile {
+    fn read(&self, writer: &mut VmWriter) -> Result<usize> {
+        if writer.avail() < core::mem::size_of::<SignalfdSiginfo>() {
+            return_errno_with_message!(Errno::EINVAL, "buf len is less than the siginfo size");
+        }
+
+        if self.is_nonblocking() {
+            self.try_read(writer)
+        } else {
+            self.wait_events(IoEvents::IN, None, || self.try_read(writer))
+        }
+    }
+
+    fn status_flags(&self) -> StatusFlags {
+        if self.is_nonblocking() {
+            StatusFlags::O_NONBLOCK
+        } else {
+            StatusFlags::empty()
+        }
+    }
+
+    fn set_status_flags(&self, new_flags: StatusFlags) -> Result<()> {
+        let mut flags = self.flags.lock();
+
+        if new_flags.contains(StatusFlags::O_NONBLOCK) {
+            *flags |= Flags::SFD_NONBLOCK;
+        } else {
+            *flags &= !Flags::SFD_NONBLOCK;
+        }
+
+        Ok(())
+    }
+
+    fn metadata(&self) -> Metadata {
+        // This is a dummy implementation.
+        // TODO: Add "anonymous inode fs" and link `SignalFile` to it.
+        let now = RealTimeClock::get().read_time();
+        Metadata {
+            dev: 0,
+            ino: 0,
+            size: 0,
+            blk_size: 0,
+            blocks: 0,
+            atime: now,
+            mtime: now,
+            ctime: now,
+            type_: InodeType::NamedPipe,
+            mode: InodeMode::from_bits_truncate(0o600),
+            nlinks: 1,
+            uid: Uid::new_root(),
+            gid: Gid::new_root(),
+            rdev: 0,
+        }
+    }
+}
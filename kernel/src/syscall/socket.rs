@@ -1,14 +1,22 @@
 // SPDX-License-Identifier: MPL-2.0
 
+use aster_bigtcp::wire::IpVersion;
+
 use super::SyscallReturn;
 use crate::{
-    fs::{file_handle::FileLike, file_table::FdFlags},
+    fs::{
+        file_handle::FileLike,
+        file_table::{max_fds, FdFlags},
+    },
     net::socket::{
-        ip::{datagram::DatagramSocket, stream::StreamSocket},
+        ip::{datagram::DatagramSocket, raw::RawSocket, stream::StreamSocket},
+        netlink::{NetlinkProtocol, NetlinkSocket},
+        packet::PacketSocket,
         unix::UnixStreamSocket,
         vsock::VsockStreamSocket,
     },
     prelude::*,
+    process::credentials::capabilities::CapSet,
     util::net::{CSocketAddrFamily, Protocol, SockFlags, SockType, SOCK_TYPE_MASK},
 };
 
@@ -16,41 +24,171 @@ pub fn sys_socket(domain: i32, type_: i32, protocol: i32, ctx: &Context) -> Resu
     let domain = CSocketAddrFamily::try_from(domain)?;
     let sock_type = SockType::try_from(type_ & SOCK_TYPE_MASK)?;
     let sock_flags = SockFlags::from_bits_truncate(type_ & !SOCK_TYPE_MASK);
-    let protocol = Protocol::try_from(protocol)?;
     debug!(
-        "domain = {:?}, sock_type = {:?}, sock_flags = {:?}, protocol = {:?}",
+        "domain = {:?}, sock_type = {:?}, sock_flags = {:?}, protocol = {}",
         domain, sock_type, sock_flags, protocol
     );
     let nonblocking = sock_flags.contains(SockFlags::SOCK_NONBLOCK);
+    let file_like = if domain == CSocketAddrFamily::AF_NETLINK {
+        new_netlink_socket(sock_type, protocol, nonblocking)?
+    } else if domain == CSocketAddrFamily::AF_PACKET {
+        new_packet_socket(sock_type, protocol, nonblocking, ctx)?
+    } else if matches!(
+        domain,
+        CSocketAddrFamily::AF_INET | CSocketAddrFamily::AF_INET6
+    ) && sock_type == SockType::SOCK_RAW
+    {
+        new_raw_socket(domain, protocol, nonblocking, ctx)?
+    } else {
+        new_socket(domain, sock_type, protocol, nonblocking)?
+    };
+    let fd = {
+        let max_fds = max_fds(&ctx.process);
+        let file_table = ctx.thread_local.file_table().borrow();
+        let mut file_table_locked = file_table.write();
+        let fd_flags = if sock_flags.contains(SockFlags::SOCK_CLOEXEC) {
+            FdFlags::CLOEXEC
+        } else {
+            FdFlags::empty()
+        };
+        file_table_locked.insert(file_like, fd_flags, max_fds)?
+    };
+    Ok(SyscallReturn::Return(fd as _))
+}
+
+fn new_socket(
+    domain: CSocketAddrFamily,
+    sock_type: SockType,
+    protocol: i32,
+    nonblocking: bool,
+) -> Result<Arc<dyn FileLike>> {
+    let protocol = Protocol::try_from(protocol)?;
     let file_like = match (domain, sock_type, protocol) {
-        // FIXME: SOCK_SEQPACKET is added to run fcntl_test, not supported yet.
-        (CSocketAddrFamily::AF_UNIX, SockType::SOCK_STREAM | SockType::SOCK_SEQPACKET, _) => {
-            UnixStreamSocket::new(nonblocking) as Arc<dyn FileLike>
+        (CSocketAddrFamily::AF_UNIX, SockType::SOCK_STREAM, _) => {
+            UnixStreamSocket::new(nonblocking, false) as Arc<dyn FileLike>
+        }
+        (CSocketAddrFamily::AF_UNIX, SockType::SOCK_SEQPACKET, _) => {
+            UnixStreamSocket::new(nonblocking, true) as Arc<dyn FileLike>
         }
         (
             CSocketAddrFamily::AF_INET,
             SockType::SOCK_STREAM,
             Protocol::IPPROTO_IP | Protocol::IPPROTO_TCP,
-        ) => StreamSocket::new(nonblocking) as Arc<dyn FileLike>,
+        ) => StreamSocket::new(IpVersion::Ipv4, nonblocking) as Arc<dyn FileLike>,
         (
             CSocketAddrFamily::AF_INET,
             SockType::SOCK_DGRAM,
             Protocol::IPPROTO_IP | Protocol::IPPROTO_UDP,
-        ) => DatagramSocket::new(nonblocking) as Arc<dyn FileLike>,
+        ) => DatagramSocket::new(IpVersion::Ipv4, nonblocking) as Arc<dyn FileLike>,
+        (
+            CSocketAddrFamily::AF_INET6,
+            SockType::SOCK_STREAM,
+            Protocol::IPPROTO_IP | Protocol::IPPROTO_TCP,
+        ) => StreamSocket::new(IpVersion::Ipv6, nonblocking) as Arc<dyn FileLike>,
+        (
+            CSocketAddrFamily::AF_INET6,
+            SockType::SOCK_DGRAM,
+            Protocol::IPPROTO_IP | Protocol::IPPROTO_UDP,
+        ) => DatagramSocket::new(IpVersion::Ipv6, nonblocking) as Arc<dyn FileLike>,
+        // TODO: Support the `net.ipv4.ping_group_range` sysctl. Currently, all users are allowed
+        // to create ping sockets.
+        (CSocketAddrFamily::AF_INET, SockType::SOCK_DGRAM, Protocol::IPPROTO_ICMP) => {
+            RawSocket::new_ping(IpVersion::Ipv4, nonblocking) as Arc<dyn FileLike>
+        }
+        (CSocketAddrFamily::AF_INET6, SockType::SOCK_DGRAM, Protocol::IPPROTO_ICMPV6) => {
+            RawSocket::new_ping(IpVersion::Ipv6, nonblocking) as Arc<dyn FileLike>
+        }
         (CSocketAddrFamily::AF_VSOCK, SockType::SOCK_STREAM, _) => {
             Arc::new(VsockStreamSocket::new(nonblocking)) as Arc<dyn FileLike>
         }
         _ => return_errno_with_message!(Errno::EAFNOSUPPORT, "unsupported domain"),
     };
-    let fd = {
-        let file_table = ctx.thread_local.file_table().borrow();
-        let mut file_table_locked = file_table.write();
-        let fd_flags = if sock_flags.contains(SockFlags::SOCK_CLOEXEC) {
-            FdFlags::CLOEXEC
-        } else {
-            FdFlags::empty()
-        };
-        file_table_locked.insert(file_like, fd_flags)
+    Ok(file_like)
+}
+
+fn new_netlink_socket(
+    sock_type: SockType,
+    protocol: i32,
+    nonblocking: bool,
+) -> Result<Arc<dyn FileLike>> {
+    // Similar to Linux, `SOCK_RAW` and `SOCK_DGRAM` are equivalent for netlink sockets.
+    if !matches!(sock_type, SockType::SOCK_RAW | SockType::SOCK_DGRAM) {
+        return_errno_with_message!(
+            Errno::ESOCKTNOSUPPORT,
+            "the socket type is not supported for netlink sockets"
+        );
+    }
+
+    let protocol = NetlinkProtocol::try_from(protocol).map_err(|_| {
+        Error::with_message(Errno::EPROTONOSUPPORT, "the netlink protocol is invalid")
+    })?;
+    Ok(NetlinkSocket::new(protocol, nonblocking)? as Arc<dyn FileLike>)
+}
+
+fn new_packet_socket(
+    sock_type: SockType,
+    protocol: i32,
+    nonblocking: bool,
+    ctx: &Context,
+) -> Result<Arc<dyn FileLike>> {
+    if !ctx
+        .posix_thread
+        .credentials()
+        .effective_capset()
+        .contains(CapSet::NET_RAW)
+    {
+        return_errno_with_message!(
+            Errno::EPERM,
+            "creating packet sockets requires the CAP_NET_RAW capability"
+        );
+    }
+
+    let is_raw = match sock_type {
+        SockType::SOCK_RAW => true,
+        SockType::SOCK_DGRAM => false,
+        _ => return_errno_with_message!(
+            Errno::ESOCKTNOSUPPORT,
+            "the socket type is not supported for packet sockets"
+        ),
     };
-    Ok(SyscallReturn::Return(fd as _))
+
+    // The protocol is in network byte order, and only the lower 16 bits are used.
+    let protocol = u16::from_be(protocol as u16);
+    Ok(PacketSocket::new(is_raw, protocol, nonblocking) as Arc<dyn FileLike>)
+}
+
+fn new_raw_socket(
+    domain: CSocketAddrFamily,
+    protocol: i32,
+    nonblocking: bool,
+    ctx: &Context,
+) -> Result<Arc<dyn FileLike>> {
+    if !ctx
+        .posix_thread
+        .credentials()
+        .effective_capset()
+        .contains(CapSet::NET_RAW)
+    {
+        return_errno_with_message!(
+            Errno::EPERM,
+            "creating raw sockets requires the CAP_NET_RAW capability"
+        );
+    }
+
+    // Like Linux, any protocol number can be used, except for `IPPROTO_IP`.
+    let protocol = match protocol {
+        1..=255 => protocol as u8,
+        0 => return_errno_with_message!(
+            Errno::EPROTONOSUPPORT,
+            "raw sockets cannot use the dummy protocol"
+        ),
+        _ => return_errno_with_message!(Errno::EINVAL, "the protocol is invalid"),
+    };
+
+    let ip_version = if domain == CSocketAddrFamily::AF_INET {
+        IpVersion::Ipv4
+    } else {
+        IpVersion::Ipv6
+    };
+    Ok(RawSocket::new_raw(ip_version, protocol, nonblocking) as Arc<dyn FileLike>)
 }
@@ -2,7 +2,7 @@
 
 use super::SyscallReturn;
 use crate::{
-    fs::file_table::{FdFlags, FileDesc},
+    fs::file_table::{max_fds, FdFlags, FileDesc},
     net::socket::unix::UnixStreamSocket,
     prelude::*,
     util::net::{CSocketAddrFamily, Protocol, SockFlags, SockType, SOCK_TYPE_MASK},
@@ -28,7 +28,10 @@ pub fn sys_socketpair(
     let nonblocking = sock_flags.contains(SockFlags::SOCK_NONBLOCK);
     let (socket_a, socket_b) = match (domain, sock_type) {
         (CSocketAddrFamily::AF_UNIX, SockType::SOCK_STREAM) => {
-            UnixStreamSocket::new_pair(nonblocking)
+            UnixStreamSocket::new_pair(nonblocking, false)
+        }
+        (CSocketAddrFamily::AF_UNIX, SockType::SOCK_SEQPACKET) => {
+            UnixStreamSocket::new_pair(nonblocking, true)
         }
         _ => return_errno_with_message!(
             Errno::EAFNOSUPPORT,
@@ -37,6 +40,7 @@ pub fn sys_socketpair(
     };
 
     let socket_fds = {
+        let max_fds = max_fds(&ctx.process);
         let file_table = ctx.thread_local.file_table().borrow();
         let mut file_table_locked = file_table.write();
         let fd_flags = if sock_flags.contains(SockFlags::SOCK_CLOEXEC) {
@@ -44,8 +48,12 @@ pub fn sys_socketpair(
         } else {
             FdFlags::empty()
         };
-        let fd_a = file_table_locked.insert(socket_a, fd_flags);
-        let fd_b = file_table_locked.insert(socket_b, fd_flags);
+        let fd_a = file_table_locked.insert(socket_a, fd_flags, max_fds)?;
+        let fd_b = file_table_locked
+            .insert(socket_b, fd_flags, max_fds)
+            .inspect_err(|_| {
+                file_table_locked.close_file(fd_a).unwrap();
+            })?;
         SocketFds(fd_a, fd_b)
     };
 
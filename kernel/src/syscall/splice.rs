@@ -0,0 +1,106 @@
+// SPDX-License-Identifier: MPL-2.0
+
+use super::SyscallReturn;
+use crate::{
+    fs::{
+        file_handle::FileLike,
+        file_table::{FileDesc, WithFileTable},
+        splice::{splice, SpliceEnd},
+        utils::{InodeType, StatusFlags},
+    },
+    prelude::*,
+};
+
+pub fn sys_splice(
+    fd_in: FileDesc,
+    off_in_ptr: Vaddr,
+    fd_out: FileDesc,
+    off_out_ptr: Vaddr,
+    len: usize,
+    flags: u32,
+    ctx: &Context,
+) -> Result<SyscallReturn> {
+    let flags = SpliceFlags::from_bits(flags)
+        .ok_or_else(|| Error::with_message(Errno::EINVAL, "invalid splice flags"))?;
+    debug!(
+        "fd_in = {}, off_in_ptr = 0x{:x}, fd_out = {}, off_out_ptr = 0x{:x}, len = 0x{:x}, flags = {:?}",
+        fd_in, off_in_ptr, fd_out, off_out_ptr, len, flags
+    );
+
+    let (in_file, out_file) = ctx
+        .thread_local
+        .file_table()
+        .borrow_mut()
+        .read_with(|inner| {
+            let in_file = inner.get_file(fd_in)?.clone();
+            let out_file = inner.get_file(fd_out)?.clone();
+            Ok::<_, Error>((in_file, out_file))
+        })?;
+
+    let is_in_pipe = is_pipe(&in_file);
+    let is_out_pipe = is_pipe(&out_file);
+    if !is_in_pipe && !is_out_pipe {
+        return_errno_with_message!(Errno::EINVAL, "neither of the files is a pipe");
+    }
+    if Arc::ptr_eq(&in_file, &out_file) {
+        return_errno_with_message!(Errno::EINVAL, "the input and output are the same pipe");
+    }
+    if out_file.status_flags().contains(StatusFlags::O_APPEND) {
+        return_errno_with_message!(Errno::EINVAL, "the output file is opened in append mode");
+    }
+
+    let mut off_in = read_offset(off_in_ptr, is_in_pipe, ctx)?;
+    let mut off_out = read_offset(off_out_ptr, is_out_pipe, ctx)?;
+
+    if len == 0 {
+        return Ok(SyscallReturn::Return(0));
+    }
+
+    let spliced_len = splice(
+        SpliceEnd::new(&in_file, off_in.as_mut()),
+        SpliceEnd::new(&out_file, off_out.as_mut()),
+        len,
+        flags.contains(SpliceFlags::SPLICE_F_NONBLOCK),
+    )?;
+
+    if let Some(off_in) = off_in {
+        ctx.user_space().write_val(off_in_ptr, &(off_in as i64))?;
+    }
+    if let Some(off_out) = off_out {
+        ctx.user_space().write_val(off_out_ptr, &(off_out as i64))?;
+    }
+
+    Ok(SyscallReturn::Return(spliced_len as _))
+}
+
+fn is_pipe(file: &Arc<dyn FileLike>) -> bool {
+    file.metadata().type_ == InodeType::NamedPipe
+}
+
+fn read_offset(offset_ptr: Vaddr, is_pipe: bool, ctx: &Context) -> Result<Option<usize>> {
+    if offset_ptr == 0 {
+        return Ok(None);
+    }
+    if is_pipe {
+        return_errno_with_message!(Errno::ESPIPE, "the offset is not allowed for a pipe");
+    }
+
+    let offset: i64 = ctx.user_space().read_val(offset_ptr)?;
+    if offset < 0 {
+        return_errno_with_message!(Errno::EINVAL, "the offset cannot be negative");
+    }
+    Ok(Some(offset as usize))
+}
+
+bitflags! {
+    pub(super) struct SpliceFlags: u32 {
+        /// Attempt to move pages instead of copying.
+        const SPLICE_F_MOVE = 1;
+        /// Do not block on I/O to the pipes.
+        const SPLICE_F_NONBLOCK = 2;
+        /// Expect more data in a subsequent splice.
+        const SPLICE_F_MORE = 4;
+        /// Unused for splice.
+        const SPLICE_F_GIFT = 8;
+    }
+}
@@ -15,15 +15,19 @@ use crate::{
 pub fn sys_fstat(fd: FileDesc, stat_buf_ptr: Vaddr, ctx: &Context) -> Result<SyscallReturn> {
     debug!("fd = {}, stat_buf_addr = 0x{:x}", fd, stat_buf_ptr);
 
-    let mut file_table = ctx.thread_local.file_table().borrow_mut();
-    let file = get_file_fast!(&mut file_table, fd);
-
-    let stat = Stat::from(file.metadata());
+    let stat = Stat::from(fstat_metadata(fd, ctx)?);
     ctx.user_space().write_val(stat_buf_ptr, &stat)?;
 
     Ok(SyscallReturn::Return(0))
 }
 
+/// Returns the metadata of the file that `fd` refers to.
+pub(super) fn fstat_metadata(fd: FileDesc, ctx: &Context) -> Result<Metadata> {
+    let mut file_table = ctx.thread_local.file_table().borrow_mut();
+    let file = get_file_fast!(&mut file_table, fd);
+    Ok(file.metadata())
+}
+
 pub fn sys_stat(filename_ptr: Vaddr, stat_buf_ptr: Vaddr, ctx: &Context) -> Result<SyscallReturn> {
     self::sys_fstatat(AT_FDCWD, filename_ptr, stat_buf_ptr, 0, ctx)
 }
@@ -45,13 +49,29 @@ pub fn sys_fstatat(
     flags: u32,
     ctx: &Context,
 ) -> Result<SyscallReturn> {
-    let user_space = ctx.user_space();
-    let filename = user_space.read_cstring(filename_ptr, MAX_FILENAME_LEN)?;
+    debug!("stat_buf_ptr = 0x{:x}", stat_buf_ptr);
+
+    let stat = Stat::from(fstatat_metadata(dirfd, filename_ptr, flags, ctx)?);
+    ctx.user_space().write_val(stat_buf_ptr, &stat)?;
+    Ok(SyscallReturn::Return(0))
+}
+
+/// Returns the metadata of the file at the path relative to `dirfd`, following the semantics
+/// of `fstatat`.
+pub(super) fn fstatat_metadata(
+    dirfd: FileDesc,
+    filename_ptr: Vaddr,
+    flags: u32,
+    ctx: &Context,
+) -> Result<Metadata> {
+    let filename = ctx
+        .user_space()
+        .read_cstring(filename_ptr, MAX_FILENAME_LEN)?;
     let flags =
         StatFlags::from_bits(flags).ok_or(Error::with_message(Errno::EINVAL, "invalid flags"))?;
     debug!(
-        "dirfd = {}, filename = {:?}, stat_buf_ptr = 0x{:x}, flags = {:?}",
-        dirfd, filename, stat_buf_ptr, flags
+        "dirfd = {}, filename = {:?}, flags = {:?}",
+        dirfd, filename, flags
     );
 
     if filename.is_empty() {
@@ -59,22 +79,21 @@ pub fn sys_fstatat(
             return_errno_with_message!(Errno::ENOENT, "path is empty");
         }
         // In this case, the behavior of fstatat() is similar to that of fstat().
-        return self::sys_fstat(dirfd, stat_buf_ptr, ctx);
+        return fstat_metadata(dirfd, ctx);
     }
 
     let dentry = {
         let filename = filename.to_string_lossy();
         let fs_path = FsPath::new(dirfd, filename.as_ref())?;
-        let fs = ctx.posix_thread.fs().resolver().read();
+        let fs_ref = ctx.thread_local.borrow_fs();
+        let fs = fs_ref.resolver().read();
         if flags.contains(StatFlags::AT_SYMLINK_NOFOLLOW) {
             fs.lookup_no_follow(&fs_path)?
         } else {
             fs.lookup(&fs_path)?
         }
     };
-    let stat = Stat::from(dentry.metadata());
-    user_space.write_val(stat_buf_ptr, &stat)?;
-    Ok(SyscallReturn::Return(0))
+    Ok(dentry.metadata())
 }
 
 /// File Stat
@@ -136,7 +155,7 @@ impl From<Metadata> for Stat {
 }
 
 bitflags::bitflags! {
-    struct StatFlags: u32 {
+    pub(super) struct StatFlags: u32 {
         const AT_EMPTY_PATH = 1 << 12;
         const AT_NO_AUTOMOUNT = 1 << 11;
         const AT_SYMLINK_NOFOLLOW = 1 << 8;
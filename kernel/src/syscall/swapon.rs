@@ -0,0 +1,68 @@
+// SPDX-License-Identifier: MPL-2.0
+
+use super::SyscallReturn;
+use crate::{
+    fs::{fs_resolver::FsPath, path::Dentry},
+    prelude::*,
+    process::credentials::capabilities::CapSet,
+    syscall::constants::MAX_FILENAME_LEN,
+    vm::swap::{swap_off, swap_on},
+};
+
+pub fn sys_swapon(path_ptr: Vaddr, flags: u32, ctx: &Context) -> Result<SyscallReturn> {
+    let flags = SwapFlags::from_bits(flags)
+        .ok_or_else(|| Error::with_message(Errno::EINVAL, "unknown flags"))?;
+    debug!("path = 0x{:x}, flags = {:?}", path_ptr, flags);
+
+    let dentry = lookup_swap_file(path_ptr, ctx)?;
+
+    let priority = flags
+        .contains(SwapFlags::SWAP_FLAG_PREFER)
+        .then(|| (flags & SwapFlags::SWAP_FLAG_PRIO_MASK).bits() as i16);
+    // TODO: Support discarding the freed swap pages.
+    swap_on(dentry, priority)?;
+
+    Ok(SyscallReturn::Return(0))
+}
+
+pub fn sys_swapoff(path_ptr: Vaddr, ctx: &Context) -> Result<SyscallReturn> {
+    debug!("path = 0x{:x}", path_ptr);
+
+    let dentry = lookup_swap_file(path_ptr, ctx)?;
+    swap_off(&dentry)?;
+
+    Ok(SyscallReturn::Return(0))
+}
+
+fn lookup_swap_file(path_ptr: Vaddr, ctx: &Context) -> Result<Dentry> {
+    if !ctx
+        .posix_thread
+        .credentials()
+        .effective_capset()
+        .contains(CapSet::SYS_ADMIN)
+    {
+        return_errno_with_message!(Errno::EPERM, "managing swap areas requires CAP_SYS_ADMIN");
+    }
+
+    let path = ctx.user_space().read_cstring(path_ptr, MAX_FILENAME_LEN)?;
+    let path = path.to_string_lossy();
+    if path.is_empty() {
+        return_errno_with_message!(Errno::ENOENT, "path is empty");
+    }
+    let fs_path = FsPath::try_from(path.as_ref())?;
+    ctx.thread_local
+        .borrow_fs()
+        .resolver()
+        .read()
+        .lookup(&fs_path)
+}
+
+bitflags! {
+    struct SwapFlags: u32 {
+        const SWAP_FLAG_PRIO_MASK     = 0x7fff;
+        const SWAP_FLAG_PREFER        = 0x8000;
+        const SWAP_FLAG_DISCARD       = 0x10000;
+        const SWAP_FLAG_DISCARD_ONCE  = 0x20000;
+        const SWAP_FLAG_DISCARD_PAGES = 0x40000;
+    }
+}
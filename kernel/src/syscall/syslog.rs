@@ -0,0 +1,181 @@
+// SPDX-License-Identifier: MPL-2.0
+
+use alloc::format;
+use core::sync::atomic::{AtomicU8, Ordering};
+
+use aster_logger::{
+    kmsg::{self, KmsgEntry, KMSG_CAPACITY, MAX_TEXT_LEN},
+    MAX_CONSOLE_LOGLEVEL, MIN_CONSOLE_LOGLEVEL,
+};
+
+use super::SyscallReturn;
+use crate::{
+    device::kmsg::{start_notifier, NEW_ENTRY_WAIT_QUEUE},
+    prelude::*,
+    process::credentials::capabilities::CapSet,
+};
+
+pub fn sys_syslog(action: i32, buf: Vaddr, len: i32, ctx: &Context) -> Result<SyscallReturn> {
+    let action = SyslogAction::try_from(action)
+        .map_err(|_| Error::with_message(Errno::EINVAL, "the action is invalid"))?;
+    debug!("action = {:?}, buf = {:#x}, len = {}", action, buf, len);
+
+    // Like Linux with `dmesg_restrict` being 0, reading all the messages and getting the size
+    // of the buffer are not restricted.
+    if !matches!(action, SyslogAction::ReadAll | SyslogAction::SizeBuffer)
+        && !ctx
+            .posix_thread
+            .credentials()
+            .effective_capset()
+            .contains(CapSet::SYSLOG)
+    {
+        return_errno_with_message!(Errno::EPERM, "the action requires CAP_SYSLOG");
+    }
+
+    let ret = match action {
+        SyslogAction::Close | SyslogAction::Open => 0,
+        SyslogAction::Read => {
+            let len = check_buffer(buf, len)?;
+            read(buf, len, ctx)?
+        }
+        SyslogAction::ReadAll | SyslogAction::ReadClear => {
+            let len = check_buffer(buf, len)?;
+            let read_len = read_all(buf, len, ctx)?;
+            if action == SyslogAction::ReadClear {
+                kmsg::clear();
+            }
+            read_len
+        }
+        SyslogAction::Clear => {
+            kmsg::clear();
+            0
+        }
+        SyslogAction::ConsoleOff => {
+            let _ = SAVED_CONSOLE_LOGLEVEL.compare_exchange(
+                0,
+                aster_logger::console_loglevel(),
+                Ordering::Relaxed,
+                Ordering::Relaxed,
+            );
+            aster_logger::set_console_loglevel(MIN_CONSOLE_LOGLEVEL);
+            0
+        }
+        SyslogAction::ConsoleOn => {
+            let saved_level = SAVED_CONSOLE_LOGLEVEL.swap(0, Ordering::Relaxed);
+            if saved_level != 0 {
+                aster_logger::set_console_loglevel(saved_level);
+            }
+            0
+        }
+        SyslogAction::ConsoleLevel => {
+            let level = u8::try_from(len)
+                .ok()
+                .filter(|level| (MIN_CONSOLE_LOGLEVEL..=MAX_CONSOLE_LOGLEVEL).contains(level))
+                .ok_or_else(|| Error::with_message(Errno::EINVAL, "the level is invalid"))?;
+            aster_logger::set_console_loglevel(level);
+            SAVED_CONSOLE_LOGLEVEL.store(0, Ordering::Relaxed);
+            0
+        }
+        SyslogAction::SizeUnread => {
+            let seq = SYSLOG_SEQ.lock().max(kmsg::first_seq());
+            (seq..kmsg::next_seq())
+                .filter_map(kmsg::read)
+                .map(|entry| format_line(&entry).len())
+                .sum()
+        }
+        SyslogAction::SizeBuffer => KMSG_CAPACITY * MAX_TEXT_LEN,
+    };
+
+    Ok(SyscallReturn::Return(ret as _))
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, TryFromInt)]
+#[repr(i32)]
+enum SyslogAction {
+    Close = 0,
+    Open = 1,
+    Read = 2,
+    ReadAll = 3,
+    ReadClear = 4,
+    Clear = 5,
+    ConsoleOff = 6,
+    ConsoleOn = 7,
+    ConsoleLevel = 8,
+    SizeUnread = 9,
+    SizeBuffer = 10,
+}
+
+/// The sequence number of the next entry to read with [`SyslogAction::Read`].
+static SYSLOG_SEQ: Mutex<u64> = Mutex::new(0);
+
+/// The console log level before [`SyslogAction::ConsoleOff`], or zero if the console is on.
+static SAVED_CONSOLE_LOGLEVEL: AtomicU8 = AtomicU8::new(0);
+
+fn check_buffer(buf: Vaddr, len: i32) -> Result<usize> {
+    if buf == 0 || len < 0 {
+        return_errno_with_message!(Errno::EINVAL, "the buffer is invalid");
+    }
+    Ok(len as usize)
+}
+
+/// Reads the entries that have not been read, waiting for new entries if there are none.
+fn read(buf: Vaddr, len: usize, ctx: &Context) -> Result<usize> {
+    if len == 0 {
+        return Ok(0);
+    }
+
+    start_notifier();
+    let mut seq = NEW_ENTRY_WAIT_QUEUE.pause_until(|| {
+        let seq = SYSLOG_SEQ.lock();
+        (*seq < kmsg::next_seq()).then_some(seq)
+    })?;
+
+    let mut output = Vec::new();
+    *seq = (*seq).max(kmsg::first_seq());
+    while let Some(entry) = kmsg::read(*seq) {
+        let line = format_line(&entry);
+        // Like Linux, an entry that does not fit is not read.
+        if output.len() + line.len() > len {
+            break;
+        }
+        output.extend_from_slice(line.as_bytes());
+        *seq += 1;
+    }
+    drop(seq);
+
+    ctx.user_space()
+        .write_bytes(buf, &mut VmReader::from(output.as_slice()))?;
+    Ok(output.len())
+}
+
+/// Reads the newest entries that fit in the buffer, since the buffer is cleared last time.
+fn read_all(buf: Vaddr, len: usize, ctx: &Context) -> Result<usize> {
+    let lines: Vec<_> = (kmsg::clear_seq()..kmsg::next_seq())
+        .filter_map(kmsg::read)
+        .map(|entry| format_line(&entry))
+        .collect();
+
+    let mut total_len: usize = lines.iter().map(String::len).sum();
+    let mut first_line = 0;
+    while total_len > len {
+        total_len -= lines[first_line].len();
+        first_line += 1;
+    }
+
+    let output = lines[first_line..].concat();
+    ctx.user_space()
+        .write_bytes(buf, &mut VmReader::from(output.as_bytes()))?;
+    Ok(output.len())
+}
+
+/// Formats the entry as a line read with `syslog(2)`.
+fn format_line(entry: &KmsgEntry) -> String {
+    let timestamp = entry.timestamp();
+    format!(
+        "<{}>[{:5}.{:06}] {}\n",
+        entry.prio(),
+        timestamp.as_secs(),
+        timestamp.subsec_micros(),
+        entry.text()
+    )
+}
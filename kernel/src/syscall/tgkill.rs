@@ -15,13 +15,38 @@ use crate::{
 
 /// tgkill send a signal to a thread with pid as its thread id, and tgid as its thread group id.
 pub fn sys_tgkill(tgid: Pid, tid: Tid, sig_num: u8, ctx: &Context) -> Result<SyscallReturn> {
+    debug!("tgid = {}, pid = {}, sig_num = {:?}", tgid, tid, sig_num);
+
+    if (tgid as i32) <= 0 {
+        return_errno_with_message!(Errno::EINVAL, "the thread group ID is invalid");
+    }
+
+    do_tkill(tid, Some(tgid), sig_num, ctx)
+}
+
+/// tkill send a signal to a thread with pid as its thread id, regardless of its thread group.
+pub fn sys_tkill(tid: Tid, sig_num: u8, ctx: &Context) -> Result<SyscallReturn> {
+    debug!("pid = {}, sig_num = {:?}", tid, sig_num);
+
+    do_tkill(tid, None, sig_num, ctx)
+}
+
+fn do_tkill(tid: Tid, tgid: Option<Pid>, sig_num: u8, ctx: &Context) -> Result<SyscallReturn> {
+    if (tid as i32) <= 0 {
+        return_errno_with_message!(Errno::EINVAL, "the thread ID is invalid");
+    }
+
     let sig_num = if sig_num == 0 {
         None
     } else {
         Some(SigNum::try_from(sig_num)?)
     };
 
-    debug!("tgid = {}, pid = {}, sig_num = {:?}", tgid, tid, sig_num);
+    let pid_ns = ctx.process.pid_ns();
+    let Some(tid) = pid_ns.global_id(tid) else {
+        return_errno_with_message!(Errno::ESRCH, "the target thread does not exist");
+    };
+    let tgid = tgid.map(|tgid| pid_ns.global_id(tgid).unwrap_or(0));
 
     let signal = sig_num.map(|sig_num| {
         let pid = ctx.process.pid();
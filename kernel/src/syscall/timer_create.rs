@@ -1,26 +1,30 @@
 // SPDX-License-Identifier: MPL-2.0
 
 use super::{
-    clock_gettime::{DynamicClockIdInfo, DynamicClockType},
+    clock_gettime::{cpu_clock_process, cpu_clock_thread, DynamicClockIdInfo, DynamicClockType},
     SyscallReturn,
 };
 use crate::{
     prelude::*,
     process::{
         posix_thread::{thread_table, AsPosixThread},
-        process_table,
         signal::{
-            c_types::{sigevent_t, SigNotify},
+            c_types::{sigevent_t, sigval_t, SigNotify},
             constants::SIGALRM,
             sig_num::SigNum,
-            signals::kernel::KernelSignal,
+            signals::timer::{TimerOverrun, TimerSignal},
         },
+        PosixTimer, Process,
     },
     syscall::ClockId,
-    thread::work_queue::{submit_work_item, work_item::WorkItem},
+    thread::{
+        work_queue::{submit_work_item, work_item::WorkItem, WorkPriority},
+        Thread,
+    },
     time::{
         clockid_t,
         clocks::{BootTimeClock, MonotonicClock, RealTimeClock},
+        Timer,
     },
 };
 
@@ -37,122 +41,202 @@ pub fn sys_timer_create(
         );
     }
 
-    let current_process = current!();
-    let sent_signal: Box<dyn Fn() + Send + Sync + 'static> = {
-        // If `sigevent_addr` is NULL, use the default method (like `sys_alarm`) to send signal.
-        if sigevent_addr == 0 {
-            let process = current_process.clone();
-            let signal = KernelSignal::new(SIGALRM);
-            Box::new(move || {
-                process.enqueue_signal(signal);
-            })
-        // Determine the timeout action through `sigevent`.
-        } else {
-            let sig_event = ctx.user_space().read_val::<sigevent_t>(sigevent_addr)?;
-            let sigev_notify = SigNotify::try_from(sig_event.sigev_notify)?;
-            let signo = sig_event.sigev_signo;
-            match sigev_notify {
-                // Do nothing when the timer is expired.
-                SigNotify::SIGEV_NONE => Box::new(|| {}),
-                // Send a signal to the current process when the timer is expired.
-                SigNotify::SIGEV_SIGNAL => {
-                    let process = current_process.clone();
-                    let signal = KernelSignal::new(SigNum::try_from(signo as u8)?);
-                    Box::new(move || {
-                        process.enqueue_signal(signal);
-                    })
-                }
-                // Spawn a posix thread to run the `sigev_function`, which is stored in
-                // `sig_event.sigev_un._sigev_thread`.
-                //
-                // TODO: enable this instructions. Currently the system does not provide an API to spawn
-                // a posix thread to run a specified function.
-                SigNotify::SIGEV_THREAD => {
-                    unimplemented!()
-                }
-                // Send a signal to the specified thread when the timer is expired.
-                SigNotify::SIGEV_THREAD_ID => {
-                    let tid = sig_event.sigev_un.read_tid() as u32;
-                    let thread = thread_table::get_thread(tid).ok_or_else(|| {
+    let notify = if sigevent_addr == 0 {
+        // If `sigevent_addr` is NULL, `SIGALRM` is sent to the process with the timer ID.
+        TimerNotify::Signal {
+            num: SIGALRM,
+            value: None,
+            target: SignalTarget::Process(Arc::downgrade(&ctx.posix_thread.process())),
+        }
+    } else {
+        let sig_event = ctx.user_space().read_val::<sigevent_t>(sigevent_addr)?;
+        TimerNotify::from_sigevent(&sig_event, ctx)?
+    };
+
+    let process_timer_manager = ctx.process.timer_manager();
+    let timer_id = process_timer_manager.add_posix_timer(|timer_id| {
+        let overrun = Arc::new(TimerOverrun::default());
+        let func = notify.into_callback(timer_id as i32, overrun.clone());
+        let timer = create_timer(clockid, func, ctx)?;
+        Ok(PosixTimer::new(timer, overrun))
+    })?;
+
+    // The timer ID is a C `int`.
+    if let Err(err) = ctx
+        .user_space()
+        .write_val(timer_id_addr, &(timer_id as i32))
+    {
+        process_timer_manager.remove_posix_timer(timer_id);
+        return Err(err);
+    }
+    Ok(SyscallReturn::Return(0))
+}
+
+pub fn sys_timer_delete(timer_id: usize, ctx: &Context) -> Result<SyscallReturn> {
+    let Some(timer) = ctx.process.timer_manager().remove_posix_timer(timer_id) else {
+        return_errno_with_message!(Errno::EINVAL, "invalid timer ID");
+    };
+
+    timer.timer().cancel();
+    Ok(SyscallReturn::Return(0))
+}
+
+/// How a POSIX timer notifies its expiration.
+enum TimerNotify {
+    None,
+    Signal {
+        num: SigNum,
+        /// The value carried by the signal, which is the timer ID if it is `None`.
+        value: Option<sigval_t>,
+        target: SignalTarget,
+    },
+}
+
+/// The receiver of the signals sent by a POSIX timer.
+///
+/// The receiver is weakly referenced since the timer is owned by the process.
+enum SignalTarget {
+    Process(Weak<Process>),
+    Thread(Weak<Thread>),
+}
+
+impl TimerNotify {
+    fn from_sigevent(sig_event: &sigevent_t, ctx: &Context) -> Result<Self> {
+        let sigev_notify = SigNotify::try_from(sig_event.sigev_notify)?;
+
+        let target = match sigev_notify {
+            // Do nothing when the timer is expired.
+            SigNotify::SIGEV_NONE => return Ok(Self::None),
+            // Send a signal to the current process when the timer is expired.
+            //
+            // Like Linux, `SIGEV_THREAD` is the same as `SIGEV_SIGNAL` here. The C library
+            // implements `SIGEV_THREAD` by creating a helper thread that waits for the signal.
+            SigNotify::SIGEV_SIGNAL | SigNotify::SIGEV_THREAD => {
+                SignalTarget::Process(Arc::downgrade(&ctx.posix_thread.process()))
+            }
+            // Send a signal to the specified thread when the timer is expired.
+            SigNotify::SIGEV_THREAD_ID => {
+                let tid = sig_event.sigev_un.read_tid() as u32;
+                let thread = ctx
+                    .process
+                    .pid_ns()
+                    .global_id(tid)
+                    .and_then(thread_table::get_thread)
+                    .ok_or_else(|| {
                         Error::with_message(Errno::EINVAL, "target thread does not exist")
                     })?;
-                    let posix_thread = thread.as_posix_thread().unwrap();
-                    if posix_thread.process().pid() != current_process.pid() {
-                        return_errno_with_message!(
-                            Errno::EINVAL,
-                            "target thread should belong to current process"
-                        );
+                let posix_thread = thread.as_posix_thread().unwrap();
+                if posix_thread.process().pid() != ctx.process.pid() {
+                    return_errno_with_message!(
+                        Errno::EINVAL,
+                        "target thread should belong to current process"
+                    );
+                }
+                SignalTarget::Thread(Arc::downgrade(&thread))
+            }
+        };
+
+        let num = u8::try_from(sig_event.sigev_signo)
+            .map_err(|_| Error::with_message(Errno::EINVAL, "invalid signal number"))?;
+        Ok(Self::Signal {
+            num: SigNum::try_from(num)?,
+            value: Some(sig_event.sigev_value),
+            target,
+        })
+    }
+
+    /// Converts the notification into the callback of the timer.
+    fn into_callback(
+        self,
+        timer_id: i32,
+        overrun: Arc<TimerOverrun>,
+    ) -> Box<dyn Fn() + Send + Sync> {
+        let Self::Signal { num, value, target } = self else {
+            return Box::new(|| {});
+        };
+        let value = value.unwrap_or_else(|| sigval_t::from_int(timer_id));
+
+        let send_signal = {
+            let overrun = overrun.clone();
+            move || {
+                let signal = TimerSignal::new(num, timer_id, value, overrun.clone());
+                match &target {
+                    SignalTarget::Process(process) => {
+                        if let Some(process) = process.upgrade() {
+                            process.enqueue_signal(signal);
+                        }
                     }
-                    let signal = KernelSignal::new(SigNum::try_from(signo as u8)?);
-                    Box::new(move || {
-                        if let Some(thread) = thread.as_posix_thread() {
-                            thread.enqueue_signal(Box::new(signal));
+                    SignalTarget::Thread(thread) => {
+                        if let Some(thread) = thread.upgrade()
+                            && let Some(posix_thread) = thread.as_posix_thread()
+                        {
+                            posix_thread.enqueue_signal(Box::new(signal));
                         }
-                    })
+                    }
                 }
             }
-        }
-    };
+        };
+        let work_item = WorkItem::new(Box::new(send_signal));
 
-    let work_func = sent_signal;
-    let work_item = WorkItem::new(work_func);
-    let func = move || {
-        submit_work_item(
-            work_item.clone(),
-            crate::thread::work_queue::WorkPriority::High,
-        );
-    };
+        Box::new(move || {
+            // The expirations while the signal is pending are counted as overruns.
+            if overrun.expire() {
+                submit_work_item(work_item.clone(), WorkPriority::High);
+            }
+        })
+    }
+}
 
-    let process_timer_manager = current_process.timer_manager();
-    let timer = if clockid >= 0 {
+/// Creates a timer based on the clock.
+fn create_timer<F>(clockid: clockid_t, func: F, ctx: &Context) -> Result<Arc<Timer>>
+where
+    F: Fn() + Send + Sync + 'static,
+{
+    if clockid >= 0 {
         let clock_id = ClockId::try_from(clockid)?;
-        match clock_id {
-            ClockId::CLOCK_PROCESS_CPUTIME_ID => process_timer_manager.create_prof_timer(func),
+        let timer = match clock_id {
+            ClockId::CLOCK_PROCESS_CPUTIME_ID => {
+                ctx.process.timer_manager().create_prof_timer(func)
+            }
             ClockId::CLOCK_THREAD_CPUTIME_ID => ctx.posix_thread.create_prof_timer(func),
             ClockId::CLOCK_REALTIME => RealTimeClock::timer_manager().create_timer(func),
             ClockId::CLOCK_MONOTONIC => MonotonicClock::timer_manager().create_timer(func),
             ClockId::CLOCK_BOOTTIME => BootTimeClock::timer_manager().create_timer(func),
-            _ => return_errno_with_message!(Errno::EINVAL, "invalid clock ID"),
-        }
-    } else {
-        let dynamic_clockid_info = DynamicClockIdInfo::try_from(clockid)?;
-        match dynamic_clockid_info {
-            DynamicClockIdInfo::Pid(pid, clock_type) => {
-                let process = process_table::get_process(pid)
-                    .ok_or_else(|| crate::Error::with_message(Errno::EINVAL, "invalid clock id"))?;
-                let process_timer_manager = process.timer_manager();
-                match clock_type {
-                    DynamicClockType::Profiling => process_timer_manager.create_prof_timer(func),
-                    DynamicClockType::Virtual => process_timer_manager.create_virtual_timer(func),
-                    // TODO: support scheduling clock and fd clock.
-                    _ => unimplemented!(),
+            _ => return_errno_with_message!(Errno::EOPNOTSUPP, "the clock does not support timers"),
+        };
+        return Ok(timer);
+    }
+
+    // The C library uses the dynamic clock IDs for the CPU-time clocks, e.g.,
+    // `CLOCK_PROCESS_CPUTIME_ID` is converted to the scheduling clock of PID 0.
+    let dynamic_clockid_info = DynamicClockIdInfo::try_from(clockid)?;
+    let timer = match dynamic_clockid_info {
+        DynamicClockIdInfo::Pid(pid, clock_type) => {
+            let process = cpu_clock_process(pid, ctx)?;
+            let process_timer_manager = process.timer_manager();
+            match clock_type {
+                // The scheduling clock is approximated by the profiling clock.
+                DynamicClockType::Profiling | DynamicClockType::Scheduling => {
+                    process_timer_manager.create_prof_timer(func)
                 }
+                DynamicClockType::Virtual => process_timer_manager.create_virtual_timer(func),
+                DynamicClockType::FD => unreachable!(),
             }
-            DynamicClockIdInfo::Tid(tid, clock_type) => {
-                let thread = thread_table::get_thread(tid)
-                    .ok_or_else(|| Error::with_message(Errno::EINVAL, "invalid clock id"))?;
-                let posix_thread = thread.as_posix_thread().unwrap();
-                match clock_type {
-                    DynamicClockType::Profiling => posix_thread.create_prof_timer(func),
-                    DynamicClockType::Virtual => posix_thread.create_virtual_timer(func),
-                    _ => unimplemented!(),
+        }
+        DynamicClockIdInfo::Tid(tid, clock_type) => {
+            let thread = cpu_clock_thread(tid, ctx)?;
+            let posix_thread = thread.as_posix_thread().unwrap();
+            match clock_type {
+                DynamicClockType::Profiling | DynamicClockType::Scheduling => {
+                    posix_thread.create_prof_timer(func)
                 }
+                DynamicClockType::Virtual => posix_thread.create_virtual_timer(func),
+                DynamicClockType::FD => unreachable!(),
             }
-            DynamicClockIdInfo::Fd(_) => unimplemented!(),
+        }
+        DynamicClockIdInfo::Fd(_) => {
+            return_errno_with_message!(Errno::EINVAL, "timers on the clock are not supported")
         }
     };
-
-    let timer_id = process_timer_manager.add_posix_timer(timer);
-    ctx.user_space().write_val(timer_id_addr, &timer_id)?;
-    Ok(SyscallReturn::Return(0))
-}
-
-pub fn sys_timer_delete(timer_id: usize, _ctx: &Context) -> Result<SyscallReturn> {
-    let current_process = current!();
-    let Some(timer) = current_process.timer_manager().remove_posix_timer(timer_id) else {
-        return_errno_with_message!(Errno::EINVAL, "invalid timer ID");
-    };
-
-    timer.cancel();
-    Ok(SyscallReturn::Return(0))
+    Ok(timer)
 }
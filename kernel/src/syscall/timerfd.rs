@@ -0,0 +1,348 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! `timerfd_create()` creates a timer that delivers timer expiration notifications via a file
+//! descriptor (we name it as `TimerFile`).
+//!
+//! Reading from `TimerFile` returns the number of expirations that have occurred since the timer
+//! was last set or read. The timer can be armed and disarmed with `timerfd_settime()`, and queried
+//! with `timerfd_gettime()`.
+//!
+//! For more detailed information about these syscalls,
+//! refer to the man 2 timerfd_create documentation.
+
+use core::{
+    sync::atomic::{AtomicBool, AtomicU64, Ordering},
+    time::Duration,
+};
+
+use super::{ClockId, SyscallReturn};
+use crate::{
+    events::IoEvents,
+    fs::{
+        file_handle::FileLike,
+        file_table::{get_file_fast, max_fds, FdFlags, FileDesc},
+        utils::{CreationFlags, InodeMode, InodeType, Metadata, StatusFlags},
+    },
+    prelude::*,
+    process::{
+        signal::{PollHandle, Pollable, Pollee},
+        Gid, Uid,
+    },
+    thread::work_queue::{submit_work_item, work_item::WorkItem, WorkPriority},
+    time::{
+        clockid_t,
+        clocks::{BootTimeClock, MonotonicClock, RealTimeClock},
+        itimerspec_t, timekeeping,
+        timer::{Timeout, Timer},
+        timespec_t,
+    },
+};
+
+pub fn sys_timerfd_create(clockid: clockid_t, flags: u32, ctx: &Context) -> Result<SyscallReturn> {
+    let flags = Flags::from_bits(flags)
+        .ok_or_else(|| Error::with_message(Errno::EINVAL, "unknown flags"))?;
+    debug!("clockid = {}, flags = {:?}", clockid, flags);
+
+    let clock_id = ClockId::try_from(clockid)?;
+    let timer_file = TimerFile::new(clock_id, flags)?;
+
+    let fd = {
+        let max_fds = max_fds(&ctx.process);
+        let file_table = ctx.thread_local.file_table().borrow();
+        let mut file_table_locked = file_table.write();
+        let fd_flags = if flags.contains(Flags::TFD_CLOEXEC) {
+            FdFlags::CLOEXEC
+        } else {
+            FdFlags::empty()
+        };
+        file_table_locked.insert(timer_file, fd_flags, max_fds)?
+    };
+
+    Ok(SyscallReturn::Return(fd as _))
+}
+
+pub fn sys_timerfd_settime(
+    fd: FileDesc,
+    flags: u32,
+    new_itimerspec_addr: Vaddr,
+    old_itimerspec_addr: Vaddr,
+    ctx: &Context,
+) -> Result<SyscallReturn> {
+    let flags = SetTimeFlags::from_bits(flags)
+        .ok_or_else(|| Error::with_message(Errno::EINVAL, "unknown flags"))?;
+    debug!(
+        "fd = {}, flags = {:?}, new_itimerspec_addr = 0x{:x}, old_itimerspec_addr = 0x{:x}",
+        fd, flags, new_itimerspec_addr, old_itimerspec_addr
+    );
+
+    let user_space = ctx.user_space();
+    let new_itimerspec = user_space.read_val::<itimerspec_t>(new_itimerspec_addr)?;
+    let interval = Duration::try_from(new_itimerspec.it_interval)?;
+    let expire_time = Duration::try_from(new_itimerspec.it_value)?;
+
+    let mut file_table = ctx.thread_local.file_table().borrow_mut();
+    let file = get_file_fast!(&mut file_table, fd);
+    let timer_file = file
+        .downcast_ref::<TimerFile>()
+        .ok_or_else(|| Error::with_message(Errno::EINVAL, "the file is not a timer file"))?;
+
+    let old_itimerspec = timer_file.set_time(flags, interval, expire_time)?;
+    if old_itimerspec_addr != 0 {
+        user_space.write_val(old_itimerspec_addr, &old_itimerspec)?;
+    }
+
+    Ok(SyscallReturn::Return(0))
+}
+
+pub fn sys_timerfd_gettime(
+    fd: FileDesc,
+    itimerspec_addr: Vaddr,
+    ctx: &Context,
+) -> Result<SyscallReturn> {
+    debug!("fd = {}, itimerspec_addr = 0x{:x}", fd, itimerspec_addr);
+
+    let mut file_table = ctx.thread_local.file_table().borrow_mut();
+    let file = get_file_fast!(&mut file_table, fd);
+    let timer_file = file
+        .downcast_ref::<TimerFile>()
+        .ok_or_else(|| Error::with_message(Errno::EINVAL, "the file is not a timer file"))?;
+
+    ctx.user_space()
+        .write_val(itimerspec_addr, &timer_file.get_time())?;
+
+    Ok(SyscallReturn::Return(0))
+}
+
+bitflags! {
+    struct Flags: u32 {
+        const TFD_CLOEXEC = CreationFlags::O_CLOEXEC.bits();
+        const TFD_NONBLOCK = StatusFlags::O_NONBLOCK.bits();
+    }
+}
+
+bitflags! {
+    struct SetTimeFlags: u32 {
+        const TFD_TIMER_ABSTIME = 1 << 0;
+        const TFD_TIMER_CANCEL_ON_SET = 1 << 1;
+    }
+}
+
+struct TimerFile {
+    clock_id: ClockId,
+    timer: Arc<Timer>,
+    /// The number of expirations that have not been read.
+    ticks: Arc<AtomicU64>,
+    /// Whether the real-time clock has been set since the timer was armed with
+    /// `TFD_TIMER_CANCEL_ON_SET`.
+    canceled: Arc<AtomicBool>,
+    /// The callback that cancels the timer when the real-time clock is set.
+    ///
+    /// The callback is registered in the timekeeping while the timer is armed with
+    /// `TFD_TIMER_CANCEL_ON_SET`, and is unregistered once it is dropped.
+    cancel_on_set: Mutex<Option<Arc<dyn Fn() + Send + Sync>>>,
+    pollee: Pollee,
+    flags: Mutex<Flags>,
+}
+
+impl TimerFile {
+    fn new(clock_id: ClockId, flags: Flags) -> Result<Arc<Self>> {
+        let ticks = Arc::new(AtomicU64::new(0));
+        let pollee = Pollee::new();
+
+        // The timer callback is executed in the interrupt context, so the pollers are notified
+        // in a work item.
+        let timer_callback = {
+            let ticks = ticks.clone();
+            let pollee = pollee.clone();
+            let work_item = WorkItem::new(Box::new(move || pollee.notify(IoEvents::IN)));
+            move || {
+                ticks.fetch_add(1, Ordering::Relaxed);
+                submit_work_item(work_item.clone(), WorkPriority::High);
+            }
+        };
+
+        let timer_manager = match clock_id {
+            ClockId::CLOCK_REALTIME => RealTimeClock::timer_manager(),
+            ClockId::CLOCK_MONOTONIC => MonotonicClock::timer_manager(),
+            ClockId::CLOCK_BOOTTIME => BootTimeClock::timer_manager(),
+            _ => return_errno_with_message!(Errno::EINVAL, "the clock is not supported"),
+        };
+        let timer = timer_manager.create_timer(timer_callback);
+
+        Ok(Arc::new(Self {
+            clock_id,
+            timer,
+            ticks,
+            canceled: Arc::new(AtomicBool::new(false)),
+            cancel_on_set: Mutex::new(None),
+            pollee,
+            flags: Mutex::new(flags),
+        }))
+    }
+
+    fn is_nonblocking(&self) -> bool {
+        self.flags.lock().contains(Flags::TFD_NONBLOCK)
+    }
+
+    /// Arms or disarms the timer, returning the old setting.
+    fn set_time(
+        &self,
+        flags: SetTimeFlags,
+        interval: Duration,
+        expire_time: Duration,
+    ) -> Result<itimerspec_t> {
+        if flags.contains(SetTimeFlags::TFD_TIMER_CANCEL_ON_SET)
+            && (!flags.contains(SetTimeFlags::TFD_TIMER_ABSTIME)
+                || self.clock_id != ClockId::CLOCK_REALTIME)
+        {
+            return_errno_with_message!(
+                Errno::EINVAL,
+                "TFD_TIMER_CANCEL_ON_SET requires an absolute timer on the real-time clock"
+            );
+        }
+
+        let old_itimerspec = self.get_time();
+
+        let mut cancel_on_set = self.cancel_on_set.lock();
+        self.timer.cancel();
+        self.ticks.store(0, Ordering::Relaxed);
+        self.canceled.store(false, Ordering::Relaxed);
+        self.pollee.invalidate();
+
+        *cancel_on_set = if flags.contains(SetTimeFlags::TFD_TIMER_CANCEL_ON_SET) {
+            let canceled = self.canceled.clone();
+            let pollee = self.pollee.clone();
+            let callback: Arc<dyn Fn() + Send + Sync> = Arc::new(move || {
+                canceled.store(true, Ordering::Relaxed);
+                pollee.notify(IoEvents::IN);
+            });
+            timekeeping::register_real_time_set_callback(&callback);
+            Some(callback)
+        } else {
+            None
+        };
+        drop(cancel_on_set);
+
+        self.timer.set_interval(interval);
+        if expire_time != Duration::ZERO {
+            let timeout = if flags.contains(SetTimeFlags::TFD_TIMER_ABSTIME) {
+                Timeout::When(expire_time)
+            } else {
+                Timeout::After(expire_time)
+            };
+            self.timer.set_timeout(timeout);
+        }
+
+        Ok(old_itimerspec)
+    }
+
+    fn get_time(&self) -> itimerspec_t {
+        itimerspec_t {
+            it_interval: timespec_t::from(self.timer.interval()),
+            it_value: timespec_t::from(self.timer.remain()),
+        }
+    }
+
+    fn try_read(&self, writer: &mut VmWriter) -> Result<()> {
+        if self.canceled.swap(false, Ordering::Relaxed) {
+            self.ticks.store(0, Ordering::Relaxed);
+            self.pollee.invalidate();
+            return_errno_with_message!(Errno::ECANCELED, "the real-time clock has been set");
+        }
+
+        let ticks = self.ticks.swap(0, Ordering::Relaxed);
+        if ticks == 0 {
+            return_errno_with_message!(Errno::EAGAIN, "the timer has not expired");
+        }
+        self.pollee.invalidate();
+
+        if let Err(err) = writer.write_val(&ticks) {
+            // Put the ticks back.
+            self.ticks.fetch_add(ticks, Ordering::Relaxed);
+            return Err(err.into());
+        }
+
+        Ok(())
+    }
+
+    fn check_io_events(&self) -> IoEvents {
+        if self.ticks.load(Ordering::Relaxed) != 0 || self.canceled.load(Ordering::Relaxed) {
+            IoEvents::IN
+        } else {
+            IoEvents::empty()
+        }
+    }
+}
+
+impl Drop for TimerFile {
+    fn drop(&mut self) {
+        self.timer.cancel();
+    }
+}
+
+impl Pollable for TimerFile {
+    fn poll(&self, mask: IoEvents, poller: Option<&mut PollHandle>) -> IoEvents {
+        self.pollee
+            .poll_with(mask, poller, || self.check_io_events())
+    }
+}
+
+impl FileLike for TimerFile {
+    fn read(&self, writer: &mut VmWriter) -> Result<usize> {
+        let read_len = core::mem::size_of::<u64>();
+
+        if writer.avail() < read_len {
+            return_errno_with_message!(Errno::EINVAL, "buf len is less than the size of u64");
+        }
+
+        if self.is_nonblocking() {
+            self.try_read(writer)?;
+        } else {
+            self.wait_events(IoEvents::IN, None, || self.try_read(writer))?;
+        }
+
+        Ok(read_len)
+    }
+
+    fn status_flags(&self) -> StatusFlags {
+        if self.is_nonblocking() {
+            StatusFlags::O_NONBLOCK
+        } else {
+            StatusFlags::empty()
+        }
+    }
+
+    fn set_status_flags(&self, new_flags: StatusFlags) -> Result<()> {
+        let mut flags = self.flags.lock();
+
+        if new_flags.contains(StatusFlags::O_NONBLOCK) {
+            *flags |= Flags::TFD_NONBLOCK;
+        } else {
+            *flags &= !Flags::TFD_NONBLOCK;
+        }
+
+        Ok(())
+    }
+
+    fn metadata(&self) -> Metadata {
+        // This is a dummy implementation.
+        // TODO: Add "anonymous inode fs" and link `TimerFile` to it.
+        let now = RealTimeClock::get().read_time();
+        Metadata {
+            dev: 0,
+            ino: 0,
+            size: 0,
+            blk_size: 0,
+            blocks: 0,
+            atime: now,
+            mtime: now,
+            ctime: now,
+            type_: InodeType::NamedPipe,
+            mode: InodeMode::from_bits_truncate(0o600),
+            nlinks: 1,
+            uid: Uid::new_root(),
+            gid: Gid::new_root(),
+            rdev: 0,
+        }
+    }
+}
@@ -0,0 +1,109 @@
+// SPDX-License-Identifier: MPL-2.0
+
+use ostd::sync::RwArc;
+
+use super::SyscallReturn;
+use crate::{
+    prelude::*,
+    process::{credentials::capabilities::CapSet, namespace::NsProxy, CloneFlags},
+};
+
+pub fn sys_unshare(unshare_flags: u64, ctx: &Context) -> Result<SyscallReturn> {
+    let mut flags = CloneFlags::from(unshare_flags);
+    debug!("flags = {:?}", flags);
+
+    check_unshare_flags(flags)?;
+
+    if flags.intersects(NsProxy::supported_flags())
+        && !ctx
+            .posix_thread
+            .credentials()
+            .effective_capset()
+            .contains(CapSet::SYS_ADMIN)
+    {
+        return_errno_with_message!(Errno::EPERM, "creating a namespace requires CAP_SYS_ADMIN");
+    }
+
+    // Like Linux, creating a new mount namespace implies unsharing the FS information, since the
+    // root and the current working directory must be moved to the new mount tree. Creating a new
+    // IPC namespace implies unsharing the semaphore adjustments, since the adjustments belong to
+    // the semaphores in the old IPC namespace.
+    if flags.contains(CloneFlags::CLONE_NEWNS) {
+        flags |= CloneFlags::CLONE_FS;
+    }
+    if flags.contains(CloneFlags::CLONE_NEWIPC) {
+        flags |= CloneFlags::CLONE_SYSVSEM;
+    }
+
+    // The semaphore adjustments are recorded per process, so they are always shared among the
+    // threads of the process.
+    if flags.contains(CloneFlags::CLONE_SYSVSEM) && ctx.process.tasks().lock().as_slice().len() > 1
+    {
+        return_errno_with_message!(
+            Errno::EINVAL,
+            "unsharing the semaphore adjustments among threads is not supported"
+        );
+    }
+
+    // The FS information is copied only if it is shared with other threads.
+    let new_fs = {
+        let fs = ctx.thread_local.borrow_fs();
+        (flags.contains(CloneFlags::CLONE_FS) && Arc::strong_count(&fs) > 1)
+            .then(|| Arc::new(fs.as_ref().clone()))
+    };
+
+    let mut ns_proxy = ctx.posix_thread.ns_proxy().lock();
+    let new_ns_proxy = {
+        let fs = ctx.thread_local.borrow_fs();
+        let mut resolver = new_fs.as_ref().unwrap_or(&*fs).resolver().write();
+        ns_proxy.copy_with_flags(flags, &mut resolver)?
+    };
+
+    // Nothing fails from now on, so the changes can be applied.
+    if flags.contains(CloneFlags::CLONE_SYSVSEM) {
+        // Like Linux, the adjustments are applied as if the process exits, so that the process
+        // starts with no adjustments.
+        ns_proxy
+            .ipc_ns()
+            .sem_sets()
+            .undo_adjustments(ctx.process.pid());
+    }
+
+    *ns_proxy = new_ns_proxy;
+    drop(ns_proxy);
+
+    if let Some(new_fs) = new_fs {
+        ctx.thread_local.set_fs(new_fs);
+    }
+
+    if flags.contains(CloneFlags::CLONE_FILES) {
+        unshare_files(ctx);
+    }
+
+    Ok(SyscallReturn::Return(0))
+}
+
+fn check_unshare_flags(flags: CloneFlags) -> Result<()> {
+    let supported_flags = CloneFlags::CLONE_FS
+        | CloneFlags::CLONE_FILES
+        | CloneFlags::CLONE_SYSVSEM
+        | NsProxy::supported_flags();
+    let unsupported_flags = flags - supported_flags;
+    if !unsupported_flags.is_empty() {
+        return_errno_with_message!(Errno::EINVAL, "unsupported unshare flags");
+    }
+
+    Ok(())
+}
+
+/// Copies the file table if it is shared with other threads or processes.
+fn unshare_files(ctx: &Context) {
+    let mut file_table = ctx.thread_local.file_table().borrow_mut();
+    if file_table.get().is_some() {
+        return;
+    }
+
+    let new_file_table = RwArc::new(file_table.read().clone());
+    *ctx.posix_thread.file_table().lock() = new_file_table.clone_ro();
+    *file_table = new_file_table;
+}
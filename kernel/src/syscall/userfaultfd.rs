@@ -0,0 +1,552 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! `userfaultfd()` creates a file descriptor (we name it as `UserfaultfdFile`) that can be
+//! used to handle the page faults of the calling process in the user space.
+//!
+//! After negotiating the API with `UFFDIO_API`, the ranges are registered with
+//! `UFFDIO_REGISTER`. The page faults in the registered ranges are read from the file as
+//! `uffd_msg` structures, and are resolved with `UFFDIO_COPY`, `UFFDIO_ZEROPAGE`,
+//! `UFFDIO_WRITEPROTECT`, or `UFFDIO_WAKE`.
+//!
+//! Only private anonymous mappings can be registered for now.
+//!
+//! For more detailed information about this syscall,
+//! refer to the man 2 userfaultfd documentation.
+
+use core::{
+    ops::Range,
+    sync::atomic::{AtomicBool, Ordering},
+};
+
+use ostd::mm::{FrameAllocOptions, UFrame, UntypedMem, MAX_USERSPACE_VADDR};
+
+use super::SyscallReturn;
+use crate::{
+    events::IoEvents,
+    fs::{
+        file_handle::FileLike,
+        file_table::{max_fds, FdFlags},
+        utils::{CreationFlags, InodeMode, InodeType, IoctlCmd, Metadata, StatusFlags},
+    },
+    prelude::*,
+    process::{
+        credentials::capabilities::CapSet,
+        signal::{PollHandle, Pollable},
+        Gid, Process, Uid,
+    },
+    time::clocks::RealTimeClock,
+    vm::{
+        memcg::alloc_user_frame,
+        userfaultfd::{Userfault, UserfaultMode, Userfaultfd},
+        util::zero_frame,
+        vmar::ROOT_VMAR_LOWEST_ADDR,
+    },
+};
+
+pub fn sys_userfaultfd(flags: u32, ctx: &Context) -> Result<SyscallReturn> {
+    let flags = Flags::from_bits(flags)
+        .ok_or_else(|| Error::with_message(Errno::EINVAL, "unknown flags"))?;
+    debug!("flags = {:?}", flags);
+
+    // TODO: Page faults in the kernel mode are also handled even if `UFFD_USER_MODE_ONLY` is
+    // specified.
+    if !flags.contains(Flags::UFFD_USER_MODE_ONLY)
+        && !ctx
+            .posix_thread
+            .credentials()
+            .effective_capset()
+            .contains(CapSet::SYS_PTRACE)
+    {
+        return_errno_with_message!(
+            Errno::EPERM,
+            "handling kernel-mode page faults requires CAP_SYS_PTRACE"
+        );
+    }
+
+    let userfaultfd_file = UserfaultfdFile::new(flags, ctx);
+    let fd = {
+        let max_fds = max_fds(&ctx.process);
+        let file_table = ctx.thread_local.file_table().borrow();
+        let mut file_table_locked = file_table.write();
+        let fd_flags = if flags.contains(Flags::O_CLOEXEC) {
+            FdFlags::CLOEXEC
+        } else {
+            FdFlags::empty()
+        };
+        file_table_locked.insert(Arc::new(userfaultfd_file), fd_flags, max_fds)?
+    };
+
+    Ok(SyscallReturn::Return(fd as _))
+}
+
+bitflags! {
+    struct Flags: u32 {
+        const UFFD_USER_MODE_ONLY = 1;
+        const O_CLOEXEC = CreationFlags::O_CLOEXEC.bits();
+        const O_NONBLOCK = StatusFlags::O_NONBLOCK.bits();
+    }
+}
+
+bitflags! {
+    /// The features that are negotiated by `UFFDIO_API`.
+    struct Features: u64 {
+        const UFFD_FEATURE_PAGEFAULT_FLAG_WP = 1 << 0;
+        const UFFD_FEATURE_THREAD_ID         = 1 << 8;
+    }
+}
+
+/// The version of the userfaultfd API.
+const UFFD_API: u64 = 0xAA;
+
+/// The event of page faults in `uffd_msg`.
+const UFFD_EVENT_PAGEFAULT: u8 = 0x12;
+
+// The bits of the ioctls that are reported by `UFFDIO_API` and `UFFDIO_REGISTER`.
+const UFFDIO_REGISTER_BIT: u64 = 1 << 0x00;
+const UFFDIO_UNREGISTER_BIT: u64 = 1 << 0x01;
+const UFFDIO_WAKE_BIT: u64 = 1 << 0x02;
+const UFFDIO_COPY_BIT: u64 = 1 << 0x03;
+const UFFDIO_ZEROPAGE_BIT: u64 = 1 << 0x04;
+const UFFDIO_WRITEPROTECT_BIT: u64 = 1 << 0x06;
+const UFFDIO_API_BIT: u64 = 1 << 0x3F;
+
+const UFFDIO_COPY_MODE_DONTWAKE: u64 = 1 << 0;
+const UFFDIO_COPY_MODE_WP: u64 = 1 << 1;
+const UFFDIO_ZEROPAGE_MODE_DONTWAKE: u64 = 1 << 0;
+const UFFDIO_WRITEPROTECT_MODE_WP: u64 = 1 << 0;
+const UFFDIO_WRITEPROTECT_MODE_DONTWAKE: u64 = 1 << 1;
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Pod)]
+struct UffdMsg {
+    event: u8,
+    reserved1: u8,
+    reserved2: u16,
+    reserved3: u32,
+    flags: u64,
+    address: u64,
+    ptid: u32,
+    _pad: u32,
+}
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Pod)]
+struct UffdioApi {
+    api: u64,
+    features: u64,
+    ioctls: u64,
+}
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Pod)]
+struct UffdioRange {
+    start: u64,
+    len: u64,
+}
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Pod)]
+struct UffdioRegister {
+    range: UffdioRange,
+    mode: u64,
+    ioctls: u64,
+}
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Pod)]
+struct UffdioCopy {
+    dst: u64,
+    src: u64,
+    len: u64,
+    mode: u64,
+    copy: i64,
+}
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Pod)]
+struct UffdioZeropage {
+    range: UffdioRange,
+    mode: u64,
+    zeropage: i64,
+}
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Pod)]
+struct UffdioWriteprotect {
+    range: UffdioRange,
+    mode: u64,
+}
+
+impl UffdioRange {
+    /// Validates the range like Linux and converts it to a range of addresses.
+    fn to_range(self) -> Result<Range<Vaddr>> {
+        let (start, len) = (self.start as Vaddr, self.len as usize);
+        if start % PAGE_SIZE != 0 || len % PAGE_SIZE != 0 || len == 0 {
+            return_errno_with_message!(Errno::EINVAL, "the range is not page-aligned or empty");
+        }
+        if start < ROOT_VMAR_LOWEST_ADDR {
+            return_errno_with_message!(Errno::EINVAL, "the range is too low");
+        }
+        if start >= MAX_USERSPACE_VADDR || len > MAX_USERSPACE_VADDR - start {
+            return_errno_with_message!(Errno::ENOMEM, "the range is not in the user space");
+        }
+        Ok(start..start + len)
+    }
+}
+
+/// A file that handles the page faults of a process.
+struct UserfaultfdFile {
+    userfaultfd: Arc<Userfaultfd>,
+    /// The features enabled by `UFFDIO_API`, or `None` if the API has not been negotiated.
+    features: Mutex<Option<Features>>,
+    is_nonblocking: AtomicBool,
+    /// The process whose page faults are handled.
+    process: Weak<Process>,
+}
+
+impl UserfaultfdFile {
+    fn new(flags: Flags, ctx: &Context) -> Self {
+        Self {
+            userfaultfd: Userfaultfd::new(),
+            features: Mutex::new(None),
+            is_nonblocking: AtomicBool::new(flags.contains(Flags::O_NONBLOCK)),
+            process: Arc::downgrade(&ctx.process),
+        }
+    }
+
+    fn features(&self) -> Result<Features> {
+        let Some(features) = *self.features.lock() else {
+            return_errno_with_message!(Errno::EINVAL, "the API has not been negotiated");
+        };
+        Ok(features)
+    }
+
+    fn process(&self) -> Result<Arc<Process>> {
+        self.process
+            .upgrade()
+            .ok_or_else(|| Error::with_message(Errno::ESRCH, "the process has exited"))
+    }
+
+    fn try_read(&self, features: Features, writer: &mut VmWriter) -> Result<usize> {
+        let msg_size = core::mem::size_of::<UffdMsg>();
+        let mut read_len = 0;
+        while writer.avail() >= msg_size {
+            let Some(fault) = self.userfaultfd.take_unread_fault() else {
+                break;
+            };
+            writer.write_val(&new_msg(fault, features))?;
+            read_len += msg_size;
+        }
+
+        if read_len == 0 {
+            return_errno_with_message!(Errno::EAGAIN, "no page faults are pending");
+        }
+        Ok(read_len)
+    }
+
+    fn api(&self, arg: usize) -> Result<()> {
+        let user_space = current_userspace!();
+        let mut api: UffdioApi = user_space.read_val(arg)?;
+
+        let mut features = self.features.lock();
+        if features.is_some() {
+            return_errno_with_message!(Errno::EINVAL, "the API has already been negotiated");
+        }
+        if api.api != UFFD_API {
+            return_errno_with_message!(Errno::EINVAL, "the API version is not supported");
+        }
+        let Some(requested) = Features::from_bits(api.features) else {
+            return_errno_with_message!(Errno::EINVAL, "the features are not supported");
+        };
+
+        api.features = Features::all().bits();
+        api.ioctls = UFFDIO_REGISTER_BIT | UFFDIO_UNREGISTER_BIT | UFFDIO_API_BIT;
+        user_space.write_val(arg, &api)?;
+        *features = Some(requested);
+
+        Ok(())
+    }
+
+    fn register(&self, arg: usize) -> Result<()> {
+        let user_space = current_userspace!();
+        let mut register: UffdioRegister = user_space.read_val(arg)?;
+
+        let range = register.range.to_range()?;
+        let Some(mode) = UserfaultMode::from_bits(register.mode).filter(|mode| !mode.is_empty())
+        else {
+            return_errno_with_message!(Errno::EINVAL, "the mode is invalid");
+        };
+
+        self.process()?
+            .root_vmar()
+            .register_userfaultfd(range, &self.userfaultfd, mode)?;
+
+        register.ioctls = UFFDIO_WAKE_BIT | UFFDIO_COPY_BIT | UFFDIO_ZEROPAGE_BIT;
+        if mode.contains(UserfaultMode::WP) {
+            register.ioctls |= UFFDIO_WRITEPROTECT_BIT;
+        }
+        user_space.write_val(arg, &register)?;
+
+        Ok(())
+    }
+
+    fn unregister(&self, arg: usize) -> Result<()> {
+        let range = current_userspace!()
+            .read_val::<UffdioRange>(arg)?
+            .to_range()?;
+
+        self.process()?
+            .root_vmar()
+            .unregister_userfaultfd(range.clone(), &self.userfaultfd)?;
+        self.userfaultfd.wake(&range);
+
+        Ok(())
+    }
+
+    fn wake(&self, arg: usize) -> Result<()> {
+        let range = current_userspace!()
+            .read_val::<UffdioRange>(arg)?
+            .to_range()?;
+        self.userfaultfd.wake(&range);
+        Ok(())
+    }
+
+    fn copy(&self, arg: usize) -> Result<()> {
+        let user_space = current_userspace!();
+        let mut copy: UffdioCopy = user_space.read_val(arg)?;
+
+        let range = UffdioRange {
+            start: copy.dst,
+            len: copy.len,
+        }
+        .to_range()?;
+        let src = copy.src as Vaddr;
+        if src % PAGE_SIZE != 0 {
+            return_errno_with_message!(Errno::EINVAL, "the source is not page-aligned");
+        }
+        if copy.mode & !(UFFDIO_COPY_MODE_DONTWAKE | UFFDIO_COPY_MODE_WP) != 0 {
+            return_errno_with_message!(Errno::EINVAL, "the mode is invalid");
+        }
+
+        let result = self.fill_pages(&range, copy.mode & UFFDIO_COPY_MODE_WP != 0, |offset| {
+            let frame = alloc_user_frame(FrameAllocOptions::new().zeroed(false), None)?;
+            user_space.read_bytes(src + offset, &mut frame.writer())?;
+            Ok(frame.into())
+        });
+        copy.copy = result_to_count(&result);
+        user_space.write_val(arg, &copy)?;
+
+        self.finish_filling(&range, result?, copy.mode & UFFDIO_COPY_MODE_DONTWAKE == 0)
+    }
+
+    fn zeropage(&self, arg: usize) -> Result<()> {
+        let user_space = current_userspace!();
+        let mut zeropage: UffdioZeropage = user_space.read_val(arg)?;
+
+        let range = zeropage.range.to_range()?;
+        if zeropage.mode & !UFFDIO_ZEROPAGE_MODE_DONTWAKE != 0 {
+            return_errno_with_message!(Errno::EINVAL, "the mode is invalid");
+        }
+
+        let result = self.fill_pages(&range, false, |_| zero_frame());
+        zeropage.zeropage = result_to_count(&result);
+        user_space.write_val(arg, &zeropage)?;
+
+        self.finish_filling(
+            &range,
+            result?,
+            zeropage.mode & UFFDIO_ZEROPAGE_MODE_DONTWAKE == 0,
+        )
+    }
+
+    /// Maps the frames prepared by `prepare_frame` to the pages within `range`.
+    ///
+    /// Returns the number of bytes that are filled. It fails only if no page is filled.
+    fn fill_pages<F>(
+        &self,
+        range: &Range<Vaddr>,
+        write_protect: bool,
+        mut prepare_frame: F,
+    ) -> Result<usize>
+    where
+        F: FnMut(usize) -> Result<UFrame>,
+    {
+        let root_vmar = self.process()?.root_vmar();
+
+        let mut offset = 0;
+        while offset < range.len() {
+            // The frame is prepared before locking the VMAR, since preparing it may trigger
+            // page faults.
+            let result = prepare_frame(offset).and_then(|frame| {
+                root_vmar.fill_userfault_page(
+                    range.start + offset,
+                    &self.userfaultfd,
+                    frame,
+                    write_protect,
+                )
+            });
+            match result {
+                Ok(()) => offset += PAGE_SIZE,
+                Err(err) if offset == 0 => return Err(err),
+                Err(_) => break,
+            }
+        }
+
+        Ok(offset)
+    }
+
+    /// Wakes up the page faults within the filled part of `range` if needed.
+    ///
+    /// Like Linux, this method fails with [`EAGAIN`] if `range` is partially filled.
+    ///
+    /// [`EAGAIN`]: Errno::EAGAIN
+    fn finish_filling(&self, range: &Range<Vaddr>, filled_len: usize, wakes: bool) -> Result<()> {
+        if wakes {
+            self.userfaultfd
+                .wake(&(range.start..range.start + filled_len));
+        }
+
+        if filled_len < range.len() {
+            return_errno_with_message!(Errno::EAGAIN, "the range is partially filled");
+        }
+        Ok(())
+    }
+
+    fn write_protect(&self, arg: usize) -> Result<()> {
+        let writeprotect: UffdioWriteprotect = current_userspace!().read_val(arg)?;
+
+        let range = writeprotect.range.to_range()?;
+        let mode = writeprotect.mode;
+        if mode & !(UFFDIO_WRITEPROTECT_MODE_WP | UFFDIO_WRITEPROTECT_MODE_DONTWAKE) != 0 {
+            return_errno_with_message!(Errno::EINVAL, "the mode is invalid");
+        }
+        let write_protect = mode & UFFDIO_WRITEPROTECT_MODE_WP != 0;
+        let wakes = mode & UFFDIO_WRITEPROTECT_MODE_DONTWAKE == 0;
+        if write_protect && !wakes {
+            return_errno_with_message!(
+                Errno::EINVAL,
+                "DONTWAKE is only allowed when removing the protection"
+            );
+        }
+
+        self.process()?.root_vmar().write_protect_userfault(
+            range.clone(),
+            &self.userfaultfd,
+            write_protect,
+        )?;
+        if !write_protect && wakes {
+            self.userfaultfd.wake(&range);
+        }
+
+        Ok(())
+    }
+}
+
+/// Converts the result of filling pages to the count reported to the user space.
+///
+/// The count is the number of bytes that are filled, or the negative error number if no page
+/// is filled.
+fn result_to_count(result: &Result<usize>) -> i64 {
+    match result {
+        Ok(filled_len) => *filled_len as i64,
+        Err(err) => -(err.error() as i64),
+    }
+}
+
+fn new_msg(fault: Userfault, features: Features) -> UffdMsg {
+    let mut msg = UffdMsg::new_zeroed();
+    msg.event = UFFD_EVENT_PAGEFAULT;
+    msg.flags = fault.flags.bits();
+    msg.address = fault.address as u64;
+    if features.contains(Features::UFFD_FEATURE_THREAD_ID) {
+        msg.ptid = fault.tid;
+    }
+    msg
+}
+
+impl Drop for UserfaultfdFile {
+    fn drop(&mut self) {
+        self.userfaultfd.release();
+    }
+}
+
+impl Pollable for UserfaultfdFile {
+    fn poll(&self, mask: IoEvents, poller: Option<&mut PollHandle>) -> IoEvents {
+        if self.features.lock().is_none() {
+            return IoEvents::ERR;
+        }
+        self.userfaultfd.poll(mask, poller)
+    }
+}
+
+impl FileLike for UserfaultfdFile {
+    fn read(&self, writer: &mut VmWriter) -> Result<usize> {
+        let features = self.features()?;
+        if writer.avail() < core::mem::size_of::<UffdMsg>() {
+            return_errno_with_message!(Errno::EINVAL, "buf len is less than the message size");
+        }
+
+        if self.is_nonblocking.load(Ordering::Relaxed) {
+            self.try_read(features, writer)
+        } else {
+            self.wait_events(IoEvents::IN, None, || self.try_read(features, writer))
+        }
+    }
+
+    fn ioctl(&self, cmd: IoctlCmd, arg: usize) -> Result<i32> {
+        if !matches!(cmd, IoctlCmd::UFFDIO_API) {
+            self.features()?;
+        }
+
+        match cmd {
+            IoctlCmd::UFFDIO_API => self.api(arg)?,
+            IoctlCmd::UFFDIO_REGISTER => self.register(arg)?,
+            IoctlCmd::UFFDIO_UNREGISTER => self.unregister(arg)?,
+            IoctlCmd::UFFDIO_WAKE => self.wake(arg)?,
+            IoctlCmd::UFFDIO_COPY => self.copy(arg)?,
+            IoctlCmd::UFFDIO_ZEROPAGE => self.zeropage(arg)?,
+            IoctlCmd::UFFDIO_WRITEPROTECT => self.write_protect(arg)?,
+            _ => return_errno_with_message!(Errno::EINVAL, "the ioctl is not supported"),
+        }
+
+        Ok(0)
+    }
+
+    fn status_flags(&self) -> StatusFlags {
+        if self.is_nonblocking.load(Ordering::Relaxed) {
+            StatusFlags::O_NONBLOCK
+        } else {
+            StatusFlags::empty()
+        }
+    }
+
+    fn set_status_flags(&self, new_flags: StatusFlags) -> Result<()> {
+        self.is_nonblocking.store(
+            new_flags.contains(StatusFlags::O_NONBLOCK),
+            Ordering::Relaxed,
+        );
+        Ok(())
+    }
+
+    fn metadata(&self) -> Metadata {
+        // This is a dummy implementation.
+        // TODO: Add "anonymous inode fs" and link `UserfaultfdFile` to it.
+        let now = RealTimeClock::get().read_time();
+        Metadata {
+            dev: 0,
+            ino: 0,
+            size: 0,
+            blk_size: 0,
+            blocks: 0,
+            atime: now,
+            mtime: now,
+            ctime: now,
+            type_: InodeType::NamedPipe,
+            mode: InodeMode::from_bits_truncate(0o600),
+            nlinks: 1,
+            uid: Uid::new_root(),
+            gid: Gid::new_root(),
+            rdev: 0,
+        }
+    }
+}
@@ -0,0 +1,82 @@
+// SPDX-License-Identifier: MPL-2.0
+
+use super::{splice::SpliceFlags, SyscallReturn};
+use crate::{
+    fs::{
+        file_handle::FileLike,
+        file_table::{get_file_fast, FileDesc},
+        pipe::{PipeReader, PipeWriter},
+        utils::StatusFlags,
+    },
+    prelude::*,
+    util::{VmReaderArray, VmWriterArray},
+};
+
+pub fn sys_vmsplice(
+    fd: FileDesc,
+    io_vec_ptr: Vaddr,
+    io_vec_count: usize,
+    flags: u32,
+    ctx: &Context,
+) -> Result<SyscallReturn> {
+    let flags = SpliceFlags::from_bits(flags)
+        .ok_or_else(|| Error::with_message(Errno::EINVAL, "invalid vmsplice flags"))?;
+    debug!(
+        "fd = {}, io_vec_ptr = 0x{:x}, io_vec_count = 0x{:x}, flags = {:?}",
+        fd, io_vec_ptr, io_vec_count, flags
+    );
+
+    let mut file_table = ctx.thread_local.file_table().borrow_mut();
+    let file = get_file_fast!(&mut file_table, fd);
+
+    let is_nonblocking = flags.contains(SpliceFlags::SPLICE_F_NONBLOCK)
+        || file.status_flags().contains(StatusFlags::O_NONBLOCK);
+
+    // The user pages are copied to (or from) the pipe buffer instead of being mapped into it.
+    // So `SPLICE_F_GIFT` has no effect.
+    let mut total_len = 0;
+    if let Some(pipe_writer) = file.downcast_ref::<PipeWriter>() {
+        let user_space = ctx.user_space();
+        let mut reader_array =
+            VmReaderArray::from_user_io_vecs(&user_space, io_vec_ptr, io_vec_count)?;
+        for reader in reader_array.readers_mut() {
+            let res = if is_nonblocking {
+                pipe_writer.try_write(reader)
+            } else {
+                pipe_writer.write(reader)
+            };
+            match res {
+                Ok(len) => total_len += len,
+                Err(_) if total_len > 0 => break,
+                Err(err) => return Err(err),
+            }
+            if reader.has_remain() {
+                break;
+            }
+        }
+    } else if let Some(pipe_reader) = file.downcast_ref::<PipeReader>() {
+        let user_space = ctx.user_space();
+        let mut writer_array =
+            VmWriterArray::from_user_io_vecs(&user_space, io_vec_ptr, io_vec_count)?;
+        for writer in writer_array.writers_mut() {
+            let res = if is_nonblocking {
+                pipe_reader.try_read(writer)
+            } else {
+                pipe_reader.read(writer)
+            };
+            match res {
+                Ok(0) => break,
+                Ok(len) => total_len += len,
+                Err(_) if total_len > 0 => break,
+                Err(err) => return Err(err),
+            }
+            if writer.has_avail() {
+                break;
+            }
+        }
+    } else {
+        return_errno_with_message!(Errno::EBADF, "the file is not a pipe");
+    }
+
+    Ok(SyscallReturn::Return(total_len as _))
+}
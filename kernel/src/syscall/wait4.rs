@@ -3,7 +3,7 @@
 use super::{getrusage::rusage_t, SyscallReturn};
 use crate::{
     prelude::*,
-    process::{wait_child_exit, ProcessFilter, WaitOptions},
+    process::{wait_child_exit, ProcessFilter, WaitOptions, WaitedChild},
 };
 
 pub fn sys_wait4(
@@ -14,7 +14,9 @@ pub fn sys_wait4(
     ctx: &Context,
 ) -> Result<SyscallReturn> {
     let wait_options = WaitOptions::from_bits(wait_options)
-        .ok_or_else(|| Error::with_message(Errno::EINVAL, "unknown wait option"))?;
+        .filter(|options| !options.intersects(WaitOptions::WEXITED | WaitOptions::WNOWAIT))
+        .ok_or_else(|| Error::with_message(Errno::EINVAL, "unknown wait option"))?
+        | WaitOptions::WEXITED;
     debug!(
         "pid = {}, exit_status_ptr = {}, wait_options: {:?}",
         wait_pid as i32, exit_status_ptr, wait_options
@@ -22,30 +24,41 @@ pub fn sys_wait4(
     debug!("wait4 current pid = {}", ctx.process.pid());
     let process_filter = ProcessFilter::from_id(wait_pid as _);
 
-    let waited_process =
+    let waited_child =
         wait_child_exit(process_filter, wait_options, ctx).map_err(|err| match err.error() {
             Errno::EINTR => Error::new(Errno::ERESTARTSYS),
             _ => err,
         })?;
-    let Some(process) = waited_process else {
+    let Some(child) = waited_child else {
         return Ok(SyscallReturn::Return(0 as _));
     };
 
-    let (return_pid, exit_code) = (process.pid(), process.status().exit_code());
+    let return_pid = ctx.process.pid_ns().local_id(child.id()).unwrap_or(0);
+    let exit_code = child.status();
     if exit_status_ptr != 0 {
         ctx.user_space()
             .write_val(exit_status_ptr as _, &exit_code)?;
     }
 
     if rusage_addr != 0 {
-        let rusage = rusage_t {
-            ru_utime: process.prof_clock().user_clock().read_time().into(),
-            ru_stime: process.prof_clock().kernel_clock().read_time().into(),
-            ..Default::default()
-        };
-
-        ctx.user_space().write_val(rusage_addr, &rusage)?;
+        write_rusage_to_user(&child, rusage_addr, ctx)?;
     }
 
     Ok(SyscallReturn::Return(return_pid as _))
 }
+
+/// Writes the resource usage of the waited child to the user space.
+pub(super) fn write_rusage_to_user(
+    child: &WaitedChild,
+    rusage_addr: Vaddr,
+    ctx: &Context,
+) -> Result<()> {
+    let prof_clock = child.prof_clock();
+    let rusage = rusage_t {
+        ru_utime: prof_clock.user_clock().read_time().into(),
+        ru_stime: prof_clock.kernel_clock().read_time().into(),
+        ..Default::default()
+    };
+
+    ctx.user_space().write_val(rusage_addr, &rusage)
+}
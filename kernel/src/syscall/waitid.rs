@@ -1,28 +1,108 @@
 // SPDX-License-Identifier: MPL-2.0
 
-use super::SyscallReturn;
+use super::{wait4::write_rusage_to_user, SyscallReturn};
 use crate::{
+    fs::file_table::{get_file_fast, FileDesc},
     prelude::*,
-    process::{wait_child_exit, ProcessFilter, WaitOptions},
+    process::{
+        signal::{
+            c_types::siginfo_t,
+            constants::{
+                CLD_CONTINUED, CLD_DUMPED, CLD_EXITED, CLD_KILLED, CLD_STOPPED, CLD_TRAPPED,
+                SIGCHLD, SIGCONT,
+            },
+        },
+        wait_child_exit, PidFile, ProcessFilter, WaitOptions, WaitedChild,
+    },
 };
 
 pub fn sys_waitid(
     which: u64,
     upid: u64,
-    _infoq_addr: u64,
+    infop_addr: Vaddr,
     options: u64,
-    _rusage_addr: u64,
+    rusage_addr: Vaddr,
     ctx: &Context,
 ) -> Result<SyscallReturn> {
-    // FIXME: what does infoq and rusage use for?
-    let process_filter = ProcessFilter::from_which_and_id(which, upid)?;
-    let wait_options = WaitOptions::from_bits(options as u32)
+    let mut wait_options = WaitOptions::from_bits(options as u32)
         .ok_or(Error::with_message(Errno::EINVAL, "invalid options"))?;
-    let waited_process =
+    debug!(
+        "which = {}, upid = {}, infop_addr = 0x{:x}, options = {:?}, rusage_addr = 0x{:x}",
+        which, upid, infop_addr, wait_options, rusage_addr
+    );
+    if !wait_options
+        .intersects(WaitOptions::WEXITED | WaitOptions::WSTOPPED | WaitOptions::WCONTINUED)
+    {
+        return_errno_with_message!(Errno::EINVAL, "no child state changes are waited for");
+    }
+
+    let mut is_nonblocking_pidfd = false;
+    let process_filter = if which == P_PIDFD {
+        let mut file_table = ctx.thread_local.file_table().borrow_mut();
+        let file = get_file_fast!(&mut file_table, upid as FileDesc);
+        let pid_file = file
+            .downcast_ref::<PidFile>()
+            .ok_or_else(|| Error::with_message(Errno::EBADF, "the file is not a pidfd"))?;
+        if pid_file.is_nonblocking() && !wait_options.contains(WaitOptions::WNOHANG) {
+            is_nonblocking_pidfd = true;
+            wait_options |= WaitOptions::WNOHANG;
+        }
+        ProcessFilter::WithPid(pid_file.process().pid())
+    } else {
+        ProcessFilter::from_which_and_id(which, upid)?
+    };
+
+    let waited_child =
         wait_child_exit(process_filter, wait_options, ctx).map_err(|err| match err.error() {
             Errno::EINTR => Error::new(Errno::ERESTARTSYS),
             _ => err,
         })?;
-    let pid = waited_process.map_or(0, |process| process.pid());
-    Ok(SyscallReturn::Return(pid as _))
+
+    let Some(child) = waited_child else {
+        // Like Linux, waiting for a non-blocking pidfd fails with `EAGAIN` instead of blocking.
+        if is_nonblocking_pidfd {
+            return_errno_with_message!(Errno::EAGAIN, "the process has not changed its state");
+        }
+        if infop_addr != 0 {
+            ctx.user_space()
+                .write_val(infop_addr, &siginfo_t::new_zeroed())?;
+        }
+        return Ok(SyscallReturn::Return(0));
+    };
+
+    if infop_addr != 0 {
+        let siginfo = child_siginfo(&child, ctx);
+        ctx.user_space().write_val(infop_addr, &siginfo)?;
+    }
+    if rusage_addr != 0 {
+        write_rusage_to_user(&child, rusage_addr, ctx)?;
+    }
+
+    Ok(SyscallReturn::Return(0))
+}
+
+/// The ID type that refers to a process by a pidfd.
+///
+/// Reference: <https://elixir.bootlin.com/linux/v6.13/source/include/uapi/linux/wait.h>
+const P_PIDFD: u64 = 3;
+
+/// Builds the `SIGCHLD` information that describes the state change of the child.
+fn child_siginfo(child: &WaitedChild, ctx: &Context) -> siginfo_t {
+    const CORE_DUMP_FLAG: u32 = 0x80;
+
+    let status = child.status();
+    let (code, si_status) = match child {
+        WaitedChild::Zombie(_) if status & 0x7f == 0 => (CLD_EXITED, (status >> 8) & 0xff),
+        WaitedChild::Zombie(_) if status & CORE_DUMP_FLAG != 0 => (CLD_DUMPED, status & 0x7f),
+        WaitedChild::Zombie(_) => (CLD_KILLED, status & 0x7f),
+        WaitedChild::Stopped(_, sig_num) => (CLD_STOPPED, sig_num.as_u8() as u32),
+        WaitedChild::Continued(_) => (CLD_CONTINUED, SIGCONT.as_u8() as u32),
+        WaitedChild::PtraceStopped(..) => (CLD_TRAPPED, status >> 8),
+    };
+
+    let mut siginfo = siginfo_t::new(SIGCHLD, code);
+    let pid = ctx.process.pid_ns().local_id(child.id()).unwrap_or(0);
+    siginfo.set_si_pid_uid(pid, child.uid());
+    siginfo.set_si_status(si_status as i32);
+    siginfo
 }
@@ -3,12 +3,19 @@
 #![allow(unused_variables)]
 
 use aster_rights::Full;
-use ostd::{cpu::*, mm::VmSpace};
+use aster_trace::{events::PAGE_FAULT, trace_event};
+use ostd::{cpu::*, mm::VmSpace, task::Task};
 
 use crate::{
     prelude::*,
-    process::signal::signals::fault::FaultSignal,
-    vm::{page_fault_handler::PageFaultHandler, perms::VmPerms, vmar::Vmar},
+    process::{
+        oom::out_of_memory,
+        posix_thread::{AsPosixThread, AsThreadLocal},
+        signal::signals::fault::FaultSignal,
+    },
+    vm::{
+        page_fault_handler::PageFaultHandler, perms::VmPerms, swap::wait_for_reclaim, vmar::Vmar,
+    },
 };
 
 /// Page fault information converted from [`CpuExceptionInfo`].
@@ -31,8 +38,16 @@ pub fn handle_exception(ctx: &Context, context: &UserContext) {
     log_trap_info(trap_info);
 
     if let Ok(page_fault_info) = PageFaultInfo::try_from(trap_info) {
-        if handle_page_fault_from_vmar(ctx.process.root_vmar(), &page_fault_info).is_ok() {
-            return;
+        match handle_page_fault_from_vmar(&ctx.thread_local.root_vmar(), &page_fault_info) {
+            Ok(()) => return,
+            // The faulting instruction will be retried after some pages are swapped out, or
+            // after some memory is freed by the OOM killer.
+            Err(err) if err.error() == Errno::ENOMEM && wait_for_reclaim() => return,
+            Err(err) if err.error() == Errno::ENOMEM && out_of_memory() => return,
+            // The faulting instruction will be retried after the signal is handled, if the
+            // page fault is interrupted when waiting for a userfaultfd.
+            Err(err) if err.error() == Errno::EINTR => return,
+            Err(_) => (),
         }
     }
 
@@ -44,8 +59,8 @@ pub(crate) fn handle_page_fault_from_vm_space(
     vm_space: &VmSpace,
     page_fault_info: &PageFaultInfo,
 ) -> core::result::Result<(), ()> {
-    let current = current!();
-    let root_vmar = current.root_vmar();
+    let current_task = Task::current().unwrap();
+    let root_vmar = current_task.as_thread_local().unwrap().root_vmar();
 
     // If page is not present or due to write access, we should ask the vmar try to commit this page
     debug_assert_eq!(
@@ -53,22 +68,31 @@ pub(crate) fn handle_page_fault_from_vm_space(
         vm_space as *const VmSpace
     );
 
-    handle_page_fault_from_vmar(root_vmar, page_fault_info)
+    handle_page_fault_from_vmar(&root_vmar, page_fault_info).map_err(|_| ())
 }
 
 /// Handles the page fault occurs in the input `Vmar`.
 pub(crate) fn handle_page_fault_from_vmar(
     root_vmar: &Vmar<Full>,
     page_fault_info: &PageFaultInfo,
-) -> core::result::Result<(), ()> {
-    if let Err(e) = root_vmar.handle_page_fault(page_fault_info) {
-        warn!(
-            "page fault handler failed: addr: 0x{:x}, err: {:?}",
-            page_fault_info.address, e
-        );
-        return Err(());
-    }
-    Ok(())
+) -> Result<()> {
+    trace_event!(
+        PAGE_FAULT,
+        Task::current()
+            .and_then(|task| task.as_posix_thread().map(|thread| thread.tid()))
+            .unwrap_or(0),
+        page_fault_info.address,
+        page_fault_info.required_perms.bits()
+    );
+
+    root_vmar
+        .handle_page_fault(page_fault_info)
+        .inspect_err(|e| {
+            warn!(
+                "page fault handler failed: addr: 0x{:x}, err: {:?}",
+                page_fault_info.address, e
+            );
+        })
 }
 
 /// generate a fault signal for current process.
@@ -0,0 +1,182 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! The hung-task detector.
+//!
+//! A task is hung if it waits uninterruptibly for a long time, e.g., for a reply from a device
+//! that never comes. Such a task cannot be killed, so it usually leads to a silent hang of the
+//! program, or of the whole system if the task holds a lock that others need.
+//!
+//! A kernel thread checks the tasks periodically, and reports the tasks that have waited
+//! uninterruptibly for longer than the timeout, with the call chains where they wait. Like
+//! Linux, a task is reported in every check until it is woken up, and the number of the
+//! reports is limited by the remaining warnings.
+//!
+//! Only the tasks of the POSIX threads are checked, since the waits of the kernel threads are
+//! not known to be interruptible or not.
+//!
+//! Reference: <https://docs.kernel.org/admin-guide/sysctl/kernel.html#hung-task-timeout-secs>
+
+use core::{
+    sync::atomic::{AtomicBool, AtomicI64, AtomicU64, Ordering},
+    time::Duration,
+};
+
+use ostd::{panic::lookup_symbol, sync::WaitQueue, task::Task};
+
+use crate::{
+    prelude::*,
+    process::{posix_thread::AsPosixThread, process_table},
+    thread::kernel_thread::ThreadOptions,
+};
+
+/// The maximum number of addresses in a reported call chain.
+const MAX_CALLCHAIN_DEPTH: usize = 32;
+
+/// The timeout in seconds, or zero if the detector is disabled.
+static TIMEOUT_SECS: AtomicU64 = AtomicU64::new(120);
+static PANIC_ON_HUNG_TASK: AtomicBool = AtomicBool::new(false);
+/// The number of the remaining reports, or a negative value if it is unlimited.
+static WARNINGS: AtomicI64 = AtomicI64::new(10);
+
+static CHECKER_WAIT_QUEUE: WaitQueue = WaitQueue::new();
+
+pub(crate) fn init() {
+    ThreadOptions::new(checker_loop).name("khungtaskd").spawn();
+}
+
+/// Returns the timeout of the hung tasks in seconds.
+///
+/// Zero means that the detector is disabled.
+pub fn timeout_secs() -> u64 {
+    TIMEOUT_SECS.load(Ordering::Relaxed)
+}
+
+/// Sets the timeout of the hung tasks in seconds.
+///
+/// Setting it to zero disables the detector.
+pub fn set_timeout_secs(secs: u64) {
+    TIMEOUT_SECS.store(secs, Ordering::Relaxed);
+    CHECKER_WAIT_QUEUE.wake_all();
+}
+
+/// Returns whether the kernel panics when a hung task is detected.
+pub fn panic_on_hung_task() -> bool {
+    PANIC_ON_HUNG_TASK.load(Ordering::Relaxed)
+}
+
+/// Sets whether the kernel panics when a hung task is detected.
+pub fn set_panic_on_hung_task(panic_on_hung_task: bool) {
+    PANIC_ON_HUNG_TASK.store(panic_on_hung_task, Ordering::Relaxed);
+}
+
+/// Returns the number of the remaining reports, or -1 if it is unlimited.
+pub fn warnings() -> i64 {
+    WARNINGS.load(Ordering::Relaxed)
+}
+
+/// Sets the number of the remaining reports.
+///
+/// Setting it to -1 makes the number unlimited.
+pub fn set_warnings(warnings: i64) -> Result<()> {
+    if warnings < -1 {
+        return_errno_with_message!(Errno::EINVAL, "the number of warnings is invalid");
+    }
+    WARNINGS.store(warnings, Ordering::Relaxed);
+    Ok(())
+}
+
+fn checker_loop() {
+    loop {
+        let timeout_secs = timeout_secs();
+        if timeout_secs == 0 {
+            CHECKER_WAIT_QUEUE.wait_until(|| (self::timeout_secs() != 0).then_some(()));
+            continue;
+        }
+
+        // Wake up early if the timeout is changed, so that the new timeout takes effect.
+        let interval = Duration::from_secs(timeout_secs);
+        let _ = CHECKER_WAIT_QUEUE.wait_until_or_timeout(
+            || (self::timeout_secs() != timeout_secs).then_some(()),
+            &interval,
+        );
+        if self::timeout_secs() != timeout_secs {
+            continue;
+        }
+
+        check_tasks(interval);
+    }
+}
+
+fn check_tasks(timeout: Duration) {
+    let processes: Vec<_> = process_table::process_table_mut().iter().cloned().collect();
+    for process in processes {
+        let tasks = process.tasks().lock().as_slice().to_vec();
+        for task in tasks {
+            if !is_hung(&task, timeout) {
+                continue;
+            }
+            if !take_warning() {
+                return;
+            }
+            report(&task, timeout);
+            if panic_on_hung_task() {
+                panic!("hung_task: blocked tasks");
+            }
+        }
+    }
+}
+
+fn is_hung(task: &Task, timeout: Duration) -> bool {
+    let Some(posix_thread) = task.as_posix_thread() else {
+        return false;
+    };
+    // The interruptible waits set the signalled waker, so that they can be woken by signals.
+    task.blocked_duration()
+        .is_some_and(|duration| duration > timeout)
+        && !posix_thread.has_signalled_waker()
+}
+
+/// Consumes one of the remaining reports, returning `false` if there are none left.
+fn take_warning() -> bool {
+    WARNINGS
+        .fetch_update(
+            Ordering::Relaxed,
+            Ordering::Relaxed,
+            |warnings| match warnings {
+                0 => None,
+                warnings if warnings < 0 => Some(warnings),
+                warnings => Some(warnings - 1),
+            },
+        )
+        .is_ok()
+}
+
+fn report(task: &Task, timeout: Duration) {
+    let posix_thread = task.as_posix_thread().unwrap();
+    let name = posix_thread
+        .thread_name()
+        .lock()
+        .as_ref()
+        .and_then(|thread_name| thread_name.name().ok().flatten())
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_default();
+
+    log::error!(
+        "INFO: task {}:{} blocked for more than {} seconds.",
+        name,
+        posix_thread.tid(),
+        timeout.as_secs()
+    );
+    log::error!("Call trace:");
+    let mut depth = 0;
+    task.walk_blocked_stack(|addr| {
+        // The addresses are return addresses. Look up the address of the call instruction,
+        // in case that the call is the last instruction of a function.
+        match lookup_symbol(addr - 1) {
+            Some((name, offset)) => log::error!("  {:#18x} <{}+{:#x}>", addr, name, offset + 1),
+            None => log::error!("  {:#18x}", addr),
+        }
+        depth += 1;
+        depth < MAX_CALLCHAIN_DEPTH
+    });
+}
@@ -1,19 +1,226 @@
 // SPDX-License-Identifier: MPL-2.0
 
+use core::sync::atomic::{AtomicBool, Ordering};
+
 use ostd::{
     cpu::CpuSet,
+    sync::{WaitQueue, Waker},
     task::{Task, TaskOptions},
+    trap::inject_irq_thread_spawner,
 };
 
 use super::{oops, AsThread, Thread};
-use crate::{prelude::*, sched::priority::Priority};
+use crate::{
+    prelude::*,
+    sched::{priority::Priority, SchedPolicy},
+};
 
 /// The inner data of a kernel thread.
-struct KernelThread;
+///
+/// Other threads can request a kernel thread to stop or to park through this
+/// structure. The kernel thread should check the requests at the points where
+/// it is safe to stop or to park, e.g., by [`KernelThread::wait_until`].
+pub struct KernelThread {
+    name: String,
+    should_stop: AtomicBool,
+    should_park: AtomicBool,
+    is_parked: AtomicBool,
+    wait_queue: WaitQueue,
+    /// The waker of the kernel thread if it pauses (see [`Pause`]).
+    ///
+    /// [`Pause`]: crate::process::signal::Pause
+    stop_waker: SpinLock<Option<Arc<Waker>>>,
+}
+
+impl KernelThread {
+    fn new(name: String) -> Self {
+        Self {
+            name,
+            should_stop: AtomicBool::new(false),
+            should_park: AtomicBool::new(false),
+            is_parked: AtomicBool::new(false),
+            wait_queue: WaitQueue::new(),
+            stop_waker: SpinLock::new(None),
+        }
+    }
+
+    /// Returns the name of the kernel thread.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Returns whether the kernel thread is requested to stop.
+    ///
+    /// The kernel thread should return from its thread function once this
+    /// method returns `true`.
+    pub fn should_stop(&self) -> bool {
+        self.should_stop.load(Ordering::Acquire)
+    }
+
+    /// Returns whether the kernel thread is requested to park.
+    pub fn should_park(&self) -> bool {
+        self.should_park.load(Ordering::Acquire)
+    }
+
+    /// Returns whether the kernel thread is parked.
+    pub fn is_parked(&self) -> bool {
+        self.is_parked.load(Ordering::Acquire)
+    }
+
+    /// Requests the kernel thread to stop.
+    ///
+    /// The kernel thread is woken if it waits in [`Self::wait_until`] or is
+    /// parked. If it pauses (see [`Pause`]), the pause is interrupted with
+    /// `EINTR`. This method does not wait for the kernel thread to exit. Use
+    /// [`Thread::join`] for that purpose.
+    ///
+    /// [`Pause`]: crate::process::signal::Pause
+    pub fn stop(&self) {
+        self.should_stop.store(true, Ordering::Release);
+        self.wait_queue.wake_all();
+        if let Some(waker) = &*self.stop_waker.lock() {
+            waker.wake_up();
+        }
+    }
+
+    /// Requests the kernel thread to park and waits until it is parked.
+    ///
+    /// A parked kernel thread does nothing until it is unparked or stopped.
+    /// The kernel thread must check the request by [`Self::wait_until`] or
+    /// [`Self::park_if_requested`], otherwise this method never returns.
+    ///
+    /// This method must not be called by the kernel thread itself.
+    pub fn park(&self) {
+        self.should_park.store(true, Ordering::Release);
+        self.wait_queue.wake_all();
+
+        while !self.is_parked() && !self.should_stop() {
+            Thread::yield_now();
+        }
+    }
+
+    /// Unparks the kernel thread.
+    pub fn unpark(&self) {
+        self.should_park.store(false, Ordering::Release);
+        self.wait_queue.wake_all();
+    }
+
+    /// Wakes the kernel thread if it waits in [`Self::wait_until`].
+    pub fn wake(&self) {
+        self.wait_queue.wake_all();
+    }
+
+    /// Sets the waker that is woken when the kernel thread is requested to stop.
+    ///
+    /// # Panics
+    ///
+    /// This method panics if the waker is already set.
+    pub(crate) fn set_stop_waker(&self, waker: Arc<Waker>) {
+        let mut stop_waker = self.stop_waker.lock();
+        assert!(stop_waker.is_none());
+        *stop_waker = Some(waker);
+    }
+
+    /// Clears the waker that is woken when the kernel thread is requested to stop.
+    pub(crate) fn clear_stop_waker(&self) {
+        *self.stop_waker.lock() = None;
+    }
+
+    /// Parks the current kernel thread if it is requested to park.
+    ///
+    /// This method returns after the kernel thread is unparked or requested to
+    /// stop. It must be called by the kernel thread itself.
+    pub fn park_if_requested(&self) {
+        if !self.should_park() {
+            return;
+        }
+
+        self.is_parked.store(true, Ordering::Release);
+        self.wait_queue
+            .wait_until(|| (!self.should_park() || self.should_stop()).then_some(()));
+        self.is_parked.store(false, Ordering::Release);
+    }
+
+    /// Waits until `cond` returns `Some(_)` or the kernel thread is requested
+    /// to stop.
+    ///
+    /// The kernel thread parks meanwhile if it is requested to park. Since
+    /// the kernel thread only checks `cond` when it is woken, the code that
+    /// makes `cond` true should call [`Self::wake`].
+    ///
+    /// This method returns `None` if the kernel thread is requested to stop.
+    /// It must be called by the kernel thread itself.
+    pub fn wait_until<F, R>(&self, mut cond: F) -> Option<R>
+    where
+        F: FnMut() -> Option<R>,
+    {
+        loop {
+            self.park_if_requested();
+
+            let wake_reason = self.wait_queue.wait_until(|| {
+                if self.should_stop() {
+                    Some(WakeReason::Stop)
+                } else if self.should_park() {
+                    Some(WakeReason::Park)
+                } else {
+                    cond().map(WakeReason::Ready)
+                }
+            });
+            match wake_reason {
+                WakeReason::Stop => return None,
+                WakeReason::Park => continue,
+                WakeReason::Ready(res) => return Some(res),
+            }
+        }
+    }
+}
+
+/// The reason why a kernel thread stops waiting in [`KernelThread::wait_until`].
+enum WakeReason<R> {
+    Stop,
+    Park,
+    Ready(R),
+}
+
+/// A trait to provide the `as_kernel_thread` method for threads and tasks.
+pub trait AsKernelThread {
+    /// Returns the associated [`KernelThread`].
+    fn as_kernel_thread(&self) -> Option<&KernelThread>;
+}
+
+impl AsKernelThread for Thread {
+    fn as_kernel_thread(&self) -> Option<&KernelThread> {
+        self.data().downcast_ref::<KernelThread>()
+    }
+}
+
+impl AsKernelThread for Task {
+    fn as_kernel_thread(&self) -> Option<&KernelThread> {
+        self.as_thread()?.as_kernel_thread()
+    }
+}
+
+/// The live kernel threads, which are listed in `/proc/kthreads`.
+static KERNEL_THREADS: SpinLock<Vec<Weak<Thread>>> = SpinLock::new(Vec::new());
+
+/// Returns all the kernel threads that have not exited.
+pub fn all_kernel_threads() -> Vec<Arc<Thread>> {
+    let kernel_threads: Vec<Arc<Thread>> = {
+        let mut kernel_threads = KERNEL_THREADS.lock();
+        kernel_threads.retain(|thread| thread.strong_count() > 0);
+        kernel_threads.iter().filter_map(Weak::upgrade).collect()
+    };
+    // The threads are dropped after the lock is released.
+    kernel_threads
+        .into_iter()
+        .filter(|thread| !thread.is_exited())
+        .collect()
+}
 
 /// Options to create or spawn a new kernel thread.
 pub struct ThreadOptions {
     func: Option<Box<dyn Fn() + Send + Sync>>,
+    name: String,
     priority: Priority,
     cpu_affinity: CpuSet,
 }
@@ -27,11 +234,18 @@ impl ThreadOptions {
         let cpu_affinity = CpuSet::new_full();
         Self {
             func: Some(Box::new(func)),
+            name: String::from("kthread"),
             priority: Priority::default(),
             cpu_affinity,
         }
     }
 
+    /// Sets the name of the new thread.
+    pub fn name(mut self, name: impl Into<String>) -> Self {
+        self.name = name.into();
+        self
+    }
+
     /// Sets the priority of the new thread.
     pub fn priority(mut self, priority: Priority) -> Self {
         self.priority = priority;
@@ -55,21 +269,26 @@ impl ThreadOptions {
             current_thread!().exit();
         };
 
-        Arc::new_cyclic(|weak_task| {
+        let task = Arc::new_cyclic(|weak_task| {
             let thread = {
-                let kernel_thread = KernelThread;
-                let priority = self.priority;
+                let kernel_thread = KernelThread::new(self.name);
+                let sched_policy = SchedPolicy::from(self.priority);
                 let cpu_affinity = self.cpu_affinity;
                 Arc::new(Thread::new(
                     weak_task.clone(),
                     kernel_thread,
-                    priority,
+                    sched_policy,
                     cpu_affinity,
                 ))
             };
 
             TaskOptions::new(thread_fn).data(thread).build().unwrap()
-        })
+        });
+
+        let thread = task.as_thread().unwrap();
+        KERNEL_THREADS.lock().push(Arc::downgrade(thread));
+
+        task
     }
 
     /// Builds a new kernel thread and runs it immediately.
@@ -81,3 +300,17 @@ impl ThreadOptions {
         thread
     }
 }
+
+/// Lets OSTD run the threaded IRQ handlers in kernel threads.
+pub(crate) fn init() {
+    inject_irq_thread_spawner(spawn_irq_thread);
+}
+
+fn spawn_irq_thread(thread_fn: Box<dyn Fn() + Send + Sync>) {
+    // Like Linux, the IRQ threads are real-time threads, so that the completions of the devices
+    // are not delayed by the normal threads.
+    ThreadOptions::new(thread_fn)
+        .name("irq")
+        .priority(Priority::default_real_time())
+        .spawn();
+}
@@ -12,15 +12,14 @@ use ostd::{
 use self::status::{AtomicThreadStatus, ThreadStatus};
 use crate::{
     prelude::*,
-    sched::{
-        priority::{AtomicPriority, Priority},
-        SchedAttr,
-    },
+    sched::{SchedAttr, SchedPolicy},
 };
 
 pub mod exception;
+pub mod hung_task;
 pub mod kernel_thread;
 pub mod oops;
+pub mod profiler;
 pub mod status;
 pub mod task;
 pub mod work_queue;
@@ -39,8 +38,6 @@ pub struct Thread {
     // mutable part
     /// Thread status
     status: AtomicThreadStatus,
-    /// Thread priority
-    priority: AtomicPriority,
     /// Thread CPU affinity
     cpu_affinity: AtomicCpuSet,
     sched_attr: SchedAttr,
@@ -51,16 +48,15 @@ impl Thread {
     pub fn new(
         task: Weak<Task>,
         data: impl Send + Sync + Any,
-        priority: Priority,
+        sched_policy: SchedPolicy,
         cpu_affinity: CpuSet,
     ) -> Self {
         Thread {
             task,
             data: Box::new(data),
             status: AtomicThreadStatus::new(ThreadStatus::Init),
-            priority: AtomicPriority::new(priority),
             cpu_affinity: AtomicCpuSet::new(cpu_affinity),
-            sched_attr: SchedAttr::new(priority.into()),
+            sched_attr: SchedAttr::new(sched_policy),
         }
     }
 
@@ -135,16 +131,12 @@ impl Thread {
         self.status.store(ThreadStatus::Exited, Ordering::Release);
     }
 
-    /// Returns the reference to the atomic priority.
-    pub fn atomic_priority(&self) -> &AtomicPriority {
-        &self.priority
-    }
-
     /// Returns the reference to the atomic CPU affinity.
     pub fn atomic_cpu_affinity(&self) -> &AtomicCpuSet {
         &self.cpu_affinity
     }
 
+    /// Returns the scheduling attribute of the thread.
     pub fn sched_attr(&self) -> &SchedAttr {
         &self.sched_attr
     }
@@ -120,11 +120,16 @@ fn panic_handler(info: &core::panic::PanicInfo) -> ! {
         );
     }
 
+    // Let the debugger inspect the panic, if it is enabled.
+    #[cfg(target_arch = "x86_64")]
+    ostd::arch::kgdb::breakpoint();
+
     if info.can_unwind() {
         panic::print_stack_trace();
     } else {
         log::error!("Backtrace is disabled.");
     }
+    panic::save_panic_report(info);
 
     panic::abort();
 }
@@ -0,0 +1,255 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! A sampling profiler.
+//!
+//! While the profiler is on, every timer interrupt takes a sample of the code that it
+//! interrupts on each CPU. A sample contains the call chain of the code, i.e., the
+//! interrupted instruction pointer followed by the return addresses of the callers,
+//! which are found by following the frame pointers.
+//!
+//! The call chain of the kernel code is unwound in the interrupt context. The call
+//! chain of the user code is not, since reading the user stack may cause page faults.
+//! Instead, the user thread is marked, and its call chain is unwound before it returns
+//! to the user mode, when its user-mode context is still the sampled one.
+//!
+//! The samples are kept in the per-CPU buffers until they are read and cleared from
+//! userspace. The addresses in the samples can be resolved against the symbols of the
+//! kernel or the user programs to find the hotspots.
+
+use alloc::format;
+use core::{
+    fmt::Write,
+    mem::size_of,
+    sync::atomic::{AtomicBool, Ordering},
+};
+
+use ostd::{
+    arch::trap::{is_kernel_interrupted, walk_kernel_stack},
+    cpu::{all_cpus, PinCurrentCpu, UserContext},
+    cpu_local,
+    sync::LocalIrqDisabled,
+    task::Task,
+    trap::{self, TrapFrame},
+    user::UserContextApi,
+};
+
+use super::{AsThread, Tid};
+use crate::{
+    cpu::LinuxAbi,
+    prelude::*,
+    process::posix_thread::{AsPosixThread, AsThreadLocal},
+    util::kptr::KernelPtr,
+};
+
+/// The maximum number of addresses in a call chain.
+const MAX_CALLCHAIN_DEPTH: usize = 32;
+
+/// The maximum number of samples kept in the buffer of each CPU.
+const MAX_SAMPLES_PER_CPU: usize = 1024;
+
+/// The offset of the frame record, i.e., the caller's frame pointer followed by the
+/// return address, from the address that a user frame pointer points to.
+#[cfg(any(target_arch = "x86_64", target_arch = "aarch64"))]
+const USER_FRAME_RECORD_OFFSET: isize = 0;
+#[cfg(target_arch = "riscv64")]
+const USER_FRAME_RECORD_OFFSET: isize = -2 * size_of::<usize>() as isize;
+
+static IS_PROFILING_ON: AtomicBool = AtomicBool::new(false);
+
+/// Serializes turning the profiler on and off.
+static SWITCH_LOCK: Mutex<()> = Mutex::new(());
+
+cpu_local! {
+    static BUFFER: SpinLock<SampleBuffer, LocalIrqDisabled> = SpinLock::new(SampleBuffer::new());
+}
+
+#[derive(Clone, Copy)]
+struct Sample {
+    /// The TID of the sampled thread, or zero if it is not a POSIX thread.
+    tid: Tid,
+    is_user: bool,
+    depth: usize,
+    callchain: [Vaddr; MAX_CALLCHAIN_DEPTH],
+}
+
+impl Sample {
+    fn new(tid: Tid, is_user: bool) -> Self {
+        Self {
+            tid,
+            is_user,
+            depth: 0,
+            callchain: [0; MAX_CALLCHAIN_DEPTH],
+        }
+    }
+
+    /// Appends an address to the call chain, returning `false` if the call chain is full.
+    fn push(&mut self, addr: Vaddr) -> bool {
+        self.callchain[self.depth] = addr;
+        self.depth += 1;
+        self.depth < MAX_CALLCHAIN_DEPTH
+    }
+}
+
+struct SampleBuffer {
+    /// The samples, which are preallocated when the profiler is turned on, so that
+    /// taking a sample never allocates memory.
+    samples: Vec<Sample>,
+    /// The number of samples dropped because the buffer is full.
+    num_lost: usize,
+}
+
+impl SampleBuffer {
+    const fn new() -> Self {
+        Self {
+            samples: Vec::new(),
+            num_lost: 0,
+        }
+    }
+
+    fn push(&mut self, sample: Sample) {
+        if self.samples.len() < self.samples.capacity() {
+            self.samples.push(sample);
+        } else {
+            self.num_lost += 1;
+        }
+    }
+}
+
+pub(crate) fn init() {
+    ostd::timer::register_sample_callback(take_sample);
+}
+
+/// Returns whether the profiler is on.
+pub(crate) fn is_profiling_on() -> bool {
+    IS_PROFILING_ON.load(Ordering::Relaxed)
+}
+
+/// Turns the profiler on or off.
+///
+/// The samples taken are kept after the profiler is turned off.
+pub(crate) fn set_profiling_on(is_on: bool) {
+    let _guard = SWITCH_LOCK.lock();
+
+    if is_on {
+        for cpu in all_cpus() {
+            // Allocate the samples before disabling IRQs.
+            let samples = Vec::with_capacity(MAX_SAMPLES_PER_CPU);
+
+            let mut buffer = BUFFER.get_on_cpu(cpu).lock();
+            if buffer.samples.capacity() == 0 {
+                buffer.samples = samples;
+            }
+        }
+    }
+    IS_PROFILING_ON.store(is_on, Ordering::Relaxed);
+}
+
+/// Discards the samples taken.
+pub(crate) fn clear() {
+    for cpu in all_cpus() {
+        let mut buffer = BUFFER.get_on_cpu(cpu).lock();
+        buffer.samples.clear();
+        buffer.num_lost = 0;
+    }
+}
+
+/// Formats the samples taken on all CPUs, one sample per line.
+pub(crate) fn read_samples() -> String {
+    let mut lines = String::new();
+    let mut num_lost = 0;
+    for cpu in all_cpus() {
+        let buffer = BUFFER.get_on_cpu(cpu).lock();
+        for sample in buffer.samples.iter() {
+            let callchain = sample.callchain[..sample.depth]
+                .iter()
+                .map(|addr| {
+                    if sample.is_user {
+                        format!(" {:#x}", addr)
+                    } else {
+                        format!(" {}", KernelPtr(*addr))
+                    }
+                })
+                .collect::<String>();
+            writeln!(
+                lines,
+                "{} {} {}{}",
+                cpu.as_usize(),
+                sample.tid,
+                if sample.is_user { "user" } else { "kernel" },
+                callchain
+            )
+            .unwrap();
+        }
+        num_lost += buffer.num_lost;
+    }
+
+    let mut output = String::new();
+    writeln!(output, "# lost samples: {}", num_lost).unwrap();
+    writeln!(output, "# CPU TID MODE CALLCHAIN").unwrap();
+    output + &lines
+}
+
+/// Takes a sample of the interrupted code in the timer interrupt.
+fn take_sample(trap_frame: &TrapFrame) {
+    if !is_profiling_on() {
+        return;
+    }
+    let Some(current_task) = Task::current() else {
+        return;
+    };
+
+    if !is_kernel_interrupted() {
+        // The call chain is unwound by `take_pending_user_sample` later.
+        if let Some(thread_local) = current_task.as_thread_local() {
+            thread_local.has_pending_user_sample().set(true);
+        }
+        return;
+    }
+
+    let tid = current_task
+        .as_thread()
+        .and_then(|thread| thread.as_posix_thread())
+        .map_or(0, |posix_thread| posix_thread.tid());
+    let mut sample = Sample::new(tid, false);
+    walk_kernel_stack(trap_frame, |addr| sample.push(addr));
+
+    let irq_guard = trap::disable_local();
+    BUFFER.get_with(&irq_guard).lock().push(sample);
+}
+
+/// Takes the pending sample of the user code of the current thread, if there is one.
+///
+/// This function should be called before the current thread returns to the user mode.
+pub(crate) fn take_pending_user_sample(ctx: &Context, user_ctx: &UserContext) {
+    if !ctx.thread_local.has_pending_user_sample().replace(false) || !is_profiling_on() {
+        return;
+    }
+
+    let mut sample = Sample::new(ctx.posix_thread.tid(), true);
+    sample.push(user_ctx.instruction_pointer());
+
+    // Follow the frame pointers like `walk_kernel_stack` does. An invalid frame pointer
+    // either fails to be read or ends the chain.
+    let user_space = ctx.user_space();
+    let mut frame_pointer = user_ctx.frame_pointer();
+    while frame_pointer % size_of::<usize>() == 0 {
+        let Some(record_addr) = frame_pointer.checked_add_signed(USER_FRAME_RECORD_OFFSET) else {
+            break;
+        };
+        let Ok([caller_frame_pointer, return_address]) =
+            user_space.read_val::<[usize; 2]>(record_addr)
+        else {
+            break;
+        };
+        if return_address == 0 || !sample.push(return_address) {
+            break;
+        }
+        if caller_frame_pointer <= frame_pointer {
+            break;
+        }
+        frame_pointer = caller_frame_pointer;
+    }
+
+    let irq_guard = trap::disable_local();
+    BUFFER.get_with(&irq_guard).lock().push(sample);
+}
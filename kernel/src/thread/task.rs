@@ -15,7 +15,7 @@ use crate::{
         signal::handle_pending_signal,
     },
     syscall::handle_syscall,
-    thread::{exception::handle_exception, AsThread},
+    thread::{exception::handle_exception, profiler, AsThread},
     vm::vmar::is_userspace_vaddr,
 };
 
@@ -56,11 +56,14 @@ pub fn create_new_user_task(
         // in the child process.
         if is_userspace_vaddr(child_tid_ptr) {
             current_userspace!()
-                .write_val(child_tid_ptr, &current_posix_thread.tid())
+                .write_val(child_tid_ptr, &current_posix_thread.ns_tid())
                 .unwrap();
         }
 
-        let has_kernel_event_fn = || current_posix_thread.has_pending();
+        let has_kernel_event_fn = || {
+            current_posix_thread.has_pending()
+                || current_thread_local.has_pending_user_sample().get()
+        };
 
         let ctx = Context {
             process: current_process.as_ref(),
@@ -83,6 +86,9 @@ pub fn create_new_user_task(
                 }
                 ReturnReason::KernelEvent => {}
             };
+            // Unwind the sampled user stack before the user-mode context is changed by
+            // the signal handlers.
+            profiler::take_pending_user_sample(&ctx, user_ctx);
 
             if current_thread.is_exited() {
                 break;
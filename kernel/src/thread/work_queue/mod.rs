@@ -63,6 +63,21 @@
 //! my_queue.enqueue(work_item);
 //!
 //! ```
+//!
+//! A work item created by `WorkItem::new` is unbound, i.e., it can be processed by the workers
+//! on any CPU. A work item created by `WorkItem::new_on_cpu` is processed only by the workers
+//! on the given CPU. A work item can also be submitted after a delay with `DelayedWorkItem`.
+//!
+//! ```rust
+//! use crate::thread::work_queue::{work_item::DelayedWorkItem, WorkItem, WorkPriority};
+//!
+//! let work_item = WorkItem::new_on_cpu(Box::new(deferred_task), CpuId::bsp());
+//! let delayed_work_item = DelayedWorkItem::new(work_item, WorkPriority::Normal);
+//! delayed_work_item.submit_after(Duration::from_millis(10));
+//! ```
+//!
+//! Work items can be submitted in the interrupt context, which is the preferred way for IRQ
+//! handlers to defer heavy processing.
 
 use intrusive_collections::linked_list::LinkedList;
 use ostd::cpu::{CpuId, CpuSet};
@@ -134,16 +149,27 @@ impl WorkQueue {
     }
 
     /// Submit a work item. Return `false` if the work item is currently pending.
+    ///
+    /// An idle worker that can process the work item is woken up, if there is one.
     pub fn enqueue(&self, work_item: Arc<WorkItem>) -> bool {
         if !work_item.try_pending() {
             return false;
         }
+        let worker_pool = self.worker_pool.upgrade();
+        let target_cpu = worker_pool
+            .as_ref()
+            .and_then(|worker_pool| worker_pool.select_cpu(&work_item));
+
         self.inner
             .disable_irq()
             .lock()
             .pending_work_items
             .push_back(work_item);
 
+        if let (Some(worker_pool), Some(target_cpu)) = (worker_pool, target_cpu) {
+            worker_pool.wake_worker(target_cpu);
+        }
+
         true
     }
 
@@ -197,7 +223,7 @@ impl Drop for WorkQueue {
     }
 }
 
-#[derive(PartialEq)]
+#[derive(PartialEq, Clone, Copy, Debug)]
 pub enum WorkPriority {
     High,
     Normal,
@@ -2,12 +2,19 @@
 
 #![allow(dead_code)]
 
-use core::sync::atomic::{AtomicBool, Ordering};
+use core::{
+    sync::atomic::{AtomicBool, Ordering},
+    time::Duration,
+};
 
 use intrusive_collections::{intrusive_adapter, LinkedListAtomicLink};
 use ostd::cpu::{CpuId, CpuSet};
 
-use crate::prelude::*;
+use super::{submit_work_item, WorkPriority};
+use crate::{
+    prelude::*,
+    time::{clocks::JIFFIES_TIMER_MANAGER, timer::Timeout, Timer},
+};
 
 /// A task to be executed by a worker thread.
 pub struct WorkItem {
@@ -30,6 +37,18 @@ impl WorkItem {
         })
     }
 
+    /// Creates a work item that is only processed by the workers on the given CPU.
+    pub fn new_on_cpu(work_func: Box<dyn Fn() + Send + Sync>, cpu_id: CpuId) -> Arc<WorkItem> {
+        let mut cpu_affinity = CpuSet::new_empty();
+        cpu_affinity.add(cpu_id);
+        Arc::new(WorkItem {
+            work_func,
+            cpu_affinity,
+            was_pending: AtomicBool::new(false),
+            link: LinkedListAtomicLink::new(),
+        })
+    }
+
     pub fn cpu_affinity(&self) -> &CpuSet {
         &self.cpu_affinity
     }
@@ -64,3 +83,39 @@ impl WorkItem {
         self.work_func.call(())
     }
 }
+
+/// A work item that is submitted to a global work queue after a delay.
+pub struct DelayedWorkItem {
+    work_item: Arc<WorkItem>,
+    timer: Arc<Timer>,
+}
+
+impl DelayedWorkItem {
+    pub fn new(work_item: Arc<WorkItem>, work_priority: WorkPriority) -> Arc<Self> {
+        let timer = {
+            let work_item = work_item.clone();
+            JIFFIES_TIMER_MANAGER.get().unwrap().create_timer(move || {
+                submit_work_item(work_item.clone(), work_priority);
+            })
+        };
+        Arc::new(DelayedWorkItem { work_item, timer })
+    }
+
+    pub fn work_item(&self) -> &Arc<WorkItem> {
+        &self.work_item
+    }
+
+    /// Submits the work item after the delay.
+    ///
+    /// If the work item is already scheduled, the delay is restarted.
+    pub fn submit_after(&self, delay: Duration) {
+        self.timer.set_timeout(Timeout::After(delay));
+    }
+
+    /// Cancels the submission if the delay has not expired.
+    ///
+    /// The work item is not removed from the work queue if it has been submitted.
+    pub fn cancel(&self) {
+        self.timer.cancel();
+    }
+}
@@ -55,6 +55,7 @@ impl Worker {
                 priority = Priority::default_real_time();
             }
             let bound_task = ThreadOptions::new(task_fn)
+                .name("kworker")
                 .cpu_affinity(cpu_affinity)
                 .priority(priority)
                 .build();
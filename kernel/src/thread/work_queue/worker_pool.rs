@@ -8,9 +8,9 @@ use core::{
 };
 
 use ostd::{
-    cpu::{CpuId, CpuSet},
+    cpu::{CpuId, CpuSet, PinCurrentCpu},
     sync::WaitQueue,
-    task::Task,
+    task::{disable_preempt, Task},
 };
 
 use super::{simple_scheduler::SimpleScheduler, worker::Worker, WorkItem, WorkPriority, WorkQueue};
@@ -184,6 +184,20 @@ impl WorkerPool {
         None
     }
 
+    /// Selects the CPU whose workers should process the work item.
+    ///
+    /// The current CPU is preferred if the work item can be processed on it.
+    pub(super) fn select_cpu(&self, work_item: &WorkItem) -> Option<CpuId> {
+        let current_cpu = disable_preempt().current_cpu();
+        if self.cpu_set.contains(current_cpu) && work_item.is_valid_cpu(current_cpu) {
+            return Some(current_cpu);
+        }
+
+        self.cpu_set
+            .iter()
+            .find(|cpu_id| work_item.is_valid_cpu(*cpu_id))
+    }
+
     fn local_pool(&self, cpu_id: CpuId) -> &Arc<LocalWorkerPool> {
         self.local_pools
             .iter()
@@ -246,6 +260,7 @@ impl Monitor {
                 WorkPriority::Normal => Priority::default(),
             };
             let bound_task = ThreadOptions::new(task_fn)
+                .name("kworker")
                 .cpu_affinity(cpu_affinity)
                 .priority(priority)
                 .build();
@@ -4,13 +4,11 @@ use alloc::sync::Arc;
 use core::time::Duration;
 
 use aster_time::read_monotonic_time;
-use ostd::{cpu::PinCurrentCpu, cpu_local, sync::SpinLock, task::disable_preempt, timer::Jiffies};
+use ostd::{cpu::PinCurrentCpu, cpu_local, sync::SeqLock, task::disable_preempt, timer::Jiffies};
 use paste::paste;
 use spin::Once;
 
-use crate::time::{
-    self, system_time::START_TIME_AS_DURATION, timer::TimerManager, Clock, SystemTime,
-};
+use crate::time::{self, timekeeping, timer::TimerManager, Clock};
 
 /// The Clock that reads the jiffies, and turn the counter into `Duration`.
 pub struct JiffiesClock {
@@ -74,8 +72,8 @@ pub struct RealTimeCoarseClock {
 
 impl RealTimeCoarseClock {
     /// A reference to the current value of this clock.
-    fn current_ref() -> &'static Once<SpinLock<Duration>> {
-        static CURRENT: Once<SpinLock<Duration>> = Once::new();
+    fn current_ref() -> &'static SeqLock<Duration> {
+        static CURRENT: SeqLock<Duration> = SeqLock::new(Duration::ZERO);
 
         &CURRENT
     }
@@ -88,7 +86,7 @@ impl RealTimeCoarseClock {
 
 /// `MonotonicCoarseClock` is a coarse-grained version of the monotonic clock.
 ///
-/// This clock is based on [`RealTimeCoarseClock`].
+/// This clock is updated together with [`RealTimeCoarseClock`].
 ///
 /// Usually it will not be used to create a timer.
 pub struct MonotonicCoarseClock {
@@ -96,6 +94,13 @@ pub struct MonotonicCoarseClock {
 }
 
 impl MonotonicCoarseClock {
+    /// A reference to the current value of this clock.
+    fn current_ref() -> &'static SeqLock<Duration> {
+        static CURRENT: SeqLock<Duration> = SeqLock::new(Duration::ZERO);
+
+        &CURRENT
+    }
+
     /// Get the singleton of this clock.
     pub fn get() -> &'static Arc<MonotonicCoarseClock> {
         CLOCK_MONOTONIC_COARSE_INSTANCE.get().unwrap()
@@ -104,9 +109,6 @@ impl MonotonicCoarseClock {
 
 /// `MonotonicRawClock` provides raw monotonic time that is not influenced by
 /// NTP corrections.
-///
-/// Note: Currently we have not implement NTP corrections so we treat this clock
-/// as the [`MonotonicClock`].
 pub struct MonotonicRawClock {
     _private: (),
 }
@@ -143,6 +145,21 @@ impl BootTimeClock {
     }
 }
 
+/// `TaiClock` provides the International Atomic Time, which is the real time
+/// without leap seconds.
+///
+/// The offset from the real time is set by NTP clients via `adjtimex`.
+pub struct TaiClock {
+    _private: (),
+}
+
+impl TaiClock {
+    /// Get the singleton of this clock.
+    pub fn get() -> &'static Arc<TaiClock> {
+        CLOCK_TAI_INSTANCE.get().unwrap()
+    }
+}
+
 impl Clock for JiffiesClock {
     fn read_time(&self) -> Duration {
         Jiffies::elapsed().as_duration()
@@ -151,27 +168,25 @@ impl Clock for JiffiesClock {
 
 impl Clock for RealTimeClock {
     fn read_time(&self) -> Duration {
-        SystemTime::now()
-            .duration_since(&SystemTime::UNIX_EPOCH)
-            .unwrap()
+        timekeeping::real_time()
     }
 }
 
 impl Clock for MonotonicClock {
     fn read_time(&self) -> Duration {
-        read_monotonic_time()
+        timekeeping::monotonic_time()
     }
 }
 
 impl Clock for RealTimeCoarseClock {
     fn read_time(&self) -> Duration {
-        *Self::current_ref().get().unwrap().disable_irq().lock()
+        Self::current_ref().read()
     }
 }
 
 impl Clock for MonotonicCoarseClock {
     fn read_time(&self) -> Duration {
-        RealTimeCoarseClock::get().read_time() - *START_TIME_AS_DURATION.get().unwrap()
+        Self::current_ref().read()
     }
 }
 
@@ -183,7 +198,13 @@ impl Clock for MonotonicRawClock {
 
 impl Clock for BootTimeClock {
     fn read_time(&self) -> Duration {
-        read_monotonic_time()
+        timekeeping::monotonic_time()
+    }
+}
+
+impl Clock for TaiClock {
+    fn read_time(&self) -> Duration {
+        timekeeping::tai_time()
     }
 }
 
@@ -231,6 +252,8 @@ macro_rules! define_timer_managers {
                         [<$clock_id _MANAGER>].get_on_cpu(cpu).call_once(|| clock_manager.clone());
                     }
                 }
+                let hint_manager = clock_manager.clone();
+                ostd::timer::register_next_event_hint(move || hint_manager.next_timeout());
                 let callback = move || {
                     clock_manager.process_expired_timers();
                 };
@@ -247,6 +270,7 @@ define_system_clocks! {
     CLOCK_MONOTONIC_COARSE  => MonotonicCoarseClock,
     CLOCK_MONOTONIC_RAW     => MonotonicRawClock,
     CLOCK_BOOTTIME          => BootTimeClock,
+    CLOCK_TAI               => TaiClock,
 }
 
 define_timer_managers![CLOCK_REALTIME, CLOCK_MONOTONIC, CLOCK_BOOTTIME,];
@@ -268,6 +292,8 @@ fn init_jiffies_clock_manager() {
     let jiffies_clock = JiffiesClock { _private: () };
     let jiffies_timer_manager = TimerManager::new(Arc::new(jiffies_clock));
     JIFFIES_TIMER_MANAGER.call_once(|| jiffies_timer_manager.clone());
+    let hint_manager = jiffies_timer_manager.clone();
+    ostd::timer::register_next_event_hint(move || hint_manager.next_timeout());
 
     let callback = move || {
         jiffies_timer_manager.process_expired_timers();
@@ -276,14 +302,13 @@ fn init_jiffies_clock_manager() {
 }
 
 fn update_coarse_clock() {
-    let real_time = RealTimeClock::get().read_time();
-    let current = RealTimeCoarseClock::current_ref().get().unwrap();
-    *current.disable_irq().lock() = real_time;
+    let snapshot = timekeeping::snapshot();
+    RealTimeCoarseClock::current_ref().set(snapshot.realtime);
+    MonotonicCoarseClock::current_ref().set(snapshot.monotonic);
 }
 
 fn init_coarse_clock() {
-    let real_time = RealTimeClock::get().read_time();
-    RealTimeCoarseClock::current_ref().call_once(|| SpinLock::new(real_time));
+    update_coarse_clock();
     time::softirq::register_callback(update_coarse_clock);
 }
 
@@ -308,7 +333,6 @@ pub fn init_for_ktest() {
         });
     }
     CLOCK_REALTIME_COARSE_INSTANCE.call_once(|| Arc::new(RealTimeCoarseClock { _private: () }));
-    RealTimeCoarseClock::current_ref().call_once(|| SpinLock::new(Duration::from_secs(0)));
     JIFFIES_TIMER_MANAGER.call_once(|| {
         let clock = JiffiesClock { _private: () };
         TimerManager::new(Arc::new(clock))
@@ -171,6 +171,19 @@ impl TimerManager {
         }
     }
 
+    /// Returns the time until the earliest expiration of the managed timers.
+    pub fn next_timeout(&self) -> Option<Duration> {
+        let mut timeout_list = self.timer_callbacks.disable_irq().lock();
+        while let Some(t) = timeout_list.peek() {
+            if !t.is_cancelled() {
+                let current_time = self.clock.read_time();
+                return Some(t.expired_time.saturating_sub(current_time));
+            }
+            timeout_list.pop();
+        }
+        None
+    }
+
     fn insert(&self, timer_callback: Arc<TimerCallback>) {
         self.timer_callbacks
             .disable_irq()
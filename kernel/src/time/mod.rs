@@ -14,6 +14,7 @@ pub mod clocks;
 mod core;
 mod softirq;
 mod system_time;
+pub mod timekeeping;
 pub mod wait;
 
 pub type clockid_t = i32;
@@ -27,6 +28,7 @@ const NSEC_PER_SEC: i64 = 1_000_000_000;
 
 pub(super) fn init() {
     system_time::init();
+    timekeeping::init();
     clocks::init();
     softirq::init();
 }
@@ -158,6 +160,37 @@ pub struct itimerval_t {
     pub it_value: timeval_t,
 }
 
+/// This struct is corresponding to the `timex` struct in Linux.
+#[repr(C)]
+#[derive(Debug, Default, Copy, Clone, Pod)]
+pub struct timex_t {
+    pub modes: u32,
+    _pad0: u32,
+    pub offset: i64,
+    pub freq: i64,
+    pub maxerror: i64,
+    pub esterror: i64,
+    pub status: i32,
+    _pad1: u32,
+    pub constant: i64,
+    pub precision: i64,
+    pub tolerance: i64,
+    /// The time, whose `usec` field is in nanoseconds if `STA_NANO` is set.
+    pub time: timeval_t,
+    pub tick: i64,
+    pub ppsfreq: i64,
+    pub jitter: i64,
+    pub shift: i32,
+    _pad2: u32,
+    pub stabil: i64,
+    pub jitcnt: i64,
+    pub calcnt: i64,
+    pub errcnt: i64,
+    pub stbcnt: i64,
+    pub tai: i32,
+    _reserved: [i32; 11],
+}
+
 /// This struct is corresponding to the `itimerspec` struct in Linux.
 #[repr(C)]
 #[derive(Debug, Default, Copy, Clone, Pod)]
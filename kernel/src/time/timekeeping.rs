@@ -0,0 +1,721 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! The timekeeping of the system-wide clocks.
+//!
+//! All system-wide clocks are derived from the raw monotonic time of the clocksource:
+//! - `CLOCK_MONOTONIC_RAW` is the raw monotonic time itself;
+//! - `CLOCK_MONOTONIC` and `CLOCK_BOOTTIME` are the raw monotonic time slewed by the NTP
+//!   adjustments, which change the rate of the clocks but never make them go backwards;
+//! - `CLOCK_REALTIME` is `CLOCK_MONOTONIC` plus an offset, which changes when the clock is set
+//!   or when a leap second occurs;
+//! - `CLOCK_TAI` is `CLOCK_REALTIME` plus the TAI offset.
+//!
+//! The NTP adjustments are made with `adjtimex(2)`, following the kernel clock discipline of
+//! Linux. A phase offset is amortized over time by the phase-locked loop (PLL), and the
+//! frequency error of the clocksource is compensated by a frequency adjustment. The
+//! adjustments are recalculated every second and take effect as the rate of the slewed clocks.
+//!
+//! A leap second is scheduled by setting `STA_INS` or `STA_DEL` and occurs at the next UTC
+//! midnight. The clocks are read with the pending leap second taken into account, so a
+//! reader never observes the time after midnight before the inserted second, regardless of
+//! when the leap second is committed by the timer.
+//!
+//! Reference: <https://www.kernel.org/doc/html/latest/timers/timekeeping.html>
+
+use core::{
+    sync::atomic::{AtomicU64, Ordering},
+    time::Duration,
+};
+
+use aster_time::read_monotonic_time;
+use ostd::sync::{LocalIrqDisabled, SeqLock, SpinLock};
+
+use super::{
+    system_time::START_TIME_AS_DURATION, timex_t, NSEC_PER_SEC, NSEC_PER_USEC, USEC_PER_SEC,
+};
+use crate::prelude::*;
+
+/// The scale of the rates and the frequencies, which are in units of 2^-16 ppm like Linux.
+const SCALED_PPM_PER_UNIT: i128 = 1_000_000 << 16;
+
+/// The maximum phase offset in nanoseconds.
+const MAX_PHASE_NS: i64 = 500_000_000;
+/// The maximum frequency adjustment in 2^-16 ppm, i.e., 500 ppm.
+const MAX_FREQ_SCALED: i64 = 500 << 16;
+/// The maximum time constant of the PLL.
+const MAX_TIME_CONSTANT: i64 = 10;
+/// The shift of the PLL time constant.
+const SHIFT_PLL: i64 = 2;
+/// The maximum rate of the single-shot adjustments in nanoseconds per second.
+const MAX_TICKADJ_NS: i64 = 500_000;
+/// The maximum error in microseconds after which the clock is unsynchronized.
+const NTP_PHASE_LIMIT_US: i64 = 16_000_000;
+/// The increase of the maximum error in microseconds per second.
+const MAX_ERROR_INCREASE_US: i64 = 500;
+/// The frequency of the clock ticks seen by the user space, e.g., the ticks in the `tick` field
+/// of `adjtimex(2)`.
+pub const USER_HZ: i64 = 100;
+/// The nominal tick length in microseconds.
+const NOMINAL_TICK_US: i64 = USEC_PER_SEC / USER_HZ;
+/// The maximum TAI offset in seconds.
+const MAX_TAI_OFFSET: i64 = 100_000;
+
+const SECS_PER_DAY: u64 = 86_400;
+
+static TIMEKEEPER: SeqLock<Timekeeper> = SeqLock::new(Timekeeper::new());
+
+static NTP: SpinLock<Ntp, LocalIrqDisabled> = SpinLock::new(Ntp::new());
+
+/// The raw monotonic time in nanoseconds when the NTP adjustments are recalculated next time.
+static NEXT_SECOND_NS: AtomicU64 = AtomicU64::new(0);
+
+/// The callbacks that are invoked when `CLOCK_REALTIME` is set discontinuously.
+///
+/// The callbacks are held weakly, so they are unregistered once their owners drop them.
+static REAL_TIME_SET_CALLBACKS: SpinLock<Vec<Weak<dyn Fn() + Send + Sync>>> =
+    SpinLock::new(Vec::new());
+
+#[derive(Clone, Copy)]
+struct Timekeeper {
+    /// The raw monotonic time when the current rate takes effect.
+    raw_base: Duration,
+    /// The slewed monotonic time at `raw_base`.
+    mono_base: Duration,
+    /// The rate adjustment of the slewed clocks in 2^-16 ppm.
+    rate: i64,
+    /// The offset from `CLOCK_MONOTONIC` to `CLOCK_REALTIME`.
+    realtime_offset: Duration,
+    /// The offset from `CLOCK_REALTIME` to `CLOCK_TAI` in seconds.
+    tai_offset: u32,
+    /// The leap second that has not been committed.
+    leap: Option<Leap>,
+}
+
+#[derive(Clone, Copy)]
+struct Leap {
+    is_insertion: bool,
+    /// The real time when the leap second occurs.
+    at: Duration,
+}
+
+/// The times of the system-wide clocks at the same instant.
+#[derive(Debug, Clone, Copy)]
+pub struct TimeSnapshot {
+    pub raw: Duration,
+    pub monotonic: Duration,
+    pub realtime: Duration,
+    pub tai: Duration,
+}
+
+impl Timekeeper {
+    const fn new() -> Self {
+        Self {
+            raw_base: Duration::ZERO,
+            mono_base: Duration::ZERO,
+            rate: 0,
+            realtime_offset: Duration::ZERO,
+            tai_offset: 0,
+            leap: None,
+        }
+    }
+
+    fn monotonic_at(&self, raw: Duration) -> Duration {
+        let delta = raw.as_nanos() as i128 - self.raw_base.as_nanos() as i128;
+        let adjusted = delta + delta * self.rate as i128 / SCALED_PPM_PER_UNIT;
+        nanos_to_duration(self.mono_base.as_nanos() as i128 + adjusted)
+    }
+
+    /// Returns the real time without taking the pending leap second into account.
+    fn unadjusted_realtime_at(&self, raw: Duration) -> Duration {
+        self.monotonic_at(raw) + self.realtime_offset
+    }
+
+    fn snapshot_at(&self, raw: Duration) -> TimeSnapshot {
+        let monotonic = self.monotonic_at(raw);
+        let mut realtime = monotonic + self.realtime_offset;
+        let mut tai = realtime + Duration::from_secs(self.tai_offset as u64);
+        if let Some(leap) = self.leap
+            && realtime >= leap.at
+        {
+            // The TAI time is continuous across a leap second.
+            if leap.is_insertion {
+                realtime = realtime.saturating_sub(Duration::from_secs(1));
+            } else {
+                realtime += Duration::from_secs(1);
+                tai -= Duration::from_secs(1);
+            }
+        }
+
+        TimeSnapshot {
+            raw,
+            monotonic,
+            realtime,
+            tai,
+        }
+    }
+
+    /// Makes the rate change take effect from the raw monotonic time.
+    fn rebase(&mut self, raw: Duration, rate: i64) {
+        self.mono_base = self.monotonic_at(raw);
+        self.raw_base = raw;
+        self.rate = rate;
+    }
+}
+
+/// The clock state returned by `adjtimex(2)`, which is mainly about the leap second.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[repr(i32)]
+pub enum TimeState {
+    Ok = 0,
+    Insert = 1,
+    Delete = 2,
+    InProgress = 3,
+    Wait = 4,
+    Error = 5,
+}
+
+bitflags! {
+    /// The NTP status flags.
+    pub struct TimexStatus: i32 {
+        const STA_PLL = 0x0001;
+        const STA_PPSFREQ = 0x0002;
+        const STA_PPSTIME = 0x0004;
+        const STA_FLL = 0x0008;
+        const STA_INS = 0x0010;
+        const STA_DEL = 0x0020;
+        const STA_UNSYNC = 0x0040;
+        const STA_FREQHOLD = 0x0080;
+        const STA_PPSSIGNAL = 0x0100;
+        const STA_PPSJITTER = 0x0200;
+        const STA_PPSWANDER = 0x0400;
+        const STA_PPSERROR = 0x0800;
+        const STA_CLOCKERR = 0x1000;
+        const STA_NANO = 0x2000;
+        const STA_MODE = 0x4000;
+        const STA_CLK = 0x8000;
+
+        /// The read-only flags.
+        const STA_RONLY = Self::STA_PPSSIGNAL.bits | Self::STA_PPSJITTER.bits
+            | Self::STA_PPSWANDER.bits | Self::STA_PPSERROR.bits
+            | Self::STA_CLOCKERR.bits | Self::STA_NANO.bits
+            | Self::STA_MODE.bits | Self::STA_CLK.bits;
+    }
+}
+
+bitflags! {
+    /// The modes of `adjtimex(2)`.
+    pub struct TimexModes: u32 {
+        const ADJ_OFFSET = 0x0001;
+        const ADJ_FREQUENCY = 0x0002;
+        const ADJ_MAXERROR = 0x0004;
+        const ADJ_ESTERROR = 0x0008;
+        const ADJ_STATUS = 0x0010;
+        const ADJ_TIMECONST = 0x0020;
+        const ADJ_TAI = 0x0080;
+        const ADJ_SETOFFSET = 0x0100;
+        const ADJ_MICRO = 0x1000;
+        const ADJ_NANO = 0x2000;
+        const ADJ_TICK = 0x4000;
+        /// The single-shot adjustment made by `adjtime(3)`, which is only valid with
+        /// `ADJ_OFFSET`.
+        const ADJ_ADJTIME = 0x8000;
+        /// Only read the single-shot adjustment, which is only valid with `ADJ_ADJTIME`.
+        const ADJ_OFFSET_READONLY = 0x2000;
+    }
+}
+
+struct Ntp {
+    state: TimeState,
+    status: TimexStatus,
+    /// The remaining phase offset in nanoseconds, which is amortized by the PLL.
+    offset_ns: i64,
+    /// The rate at which the phase offset is being amortized, in nanoseconds per second.
+    offset_rate_ns: i64,
+    /// The remaining single-shot adjustment in nanoseconds, which is made by `adjtime(3)`.
+    adjust_ns: i64,
+    /// The rate at which the single-shot adjustment is being made, in nanoseconds per second.
+    adjust_rate_ns: i64,
+    /// The frequency adjustment in 2^-16 ppm.
+    freq: i64,
+    /// The length of a `USER_HZ` tick in microseconds.
+    tick_us: i64,
+    max_error_us: i64,
+    est_error_us: i64,
+    /// The time constant of the PLL.
+    time_constant: i64,
+    /// The monotonic time in seconds when the phase offset is updated last time.
+    ref_time_secs: u64,
+    /// The real time when the current leap second ends.
+    leap_end: Duration,
+    /// The raw monotonic time when the NTP adjustments are recalculated last time.
+    last_second: Duration,
+}
+
+impl Ntp {
+    const fn new() -> Self {
+        Self {
+            state: TimeState::Ok,
+            status: TimexStatus::STA_UNSYNC,
+            offset_ns: 0,
+            offset_rate_ns: 0,
+            adjust_ns: 0,
+            adjust_rate_ns: 0,
+            freq: 0,
+            tick_us: NOMINAL_TICK_US,
+            max_error_us: NTP_PHASE_LIMIT_US,
+            est_error_us: NTP_PHASE_LIMIT_US,
+            time_constant: 2,
+            ref_time_secs: 0,
+            leap_end: Duration::ZERO,
+            last_second: Duration::ZERO,
+        }
+    }
+
+    /// Returns the rate of the slewed clocks in 2^-16 ppm.
+    fn rate(&self) -> i64 {
+        let phase_ns = self.offset_rate_ns
+            + self.adjust_rate_ns
+            + (self.tick_us - NOMINAL_TICK_US) * (USER_HZ * NSEC_PER_USEC);
+        self.freq + nanos_per_sec_to_scaled_ppm(phase_ns)
+    }
+
+    /// Accounts for the adjustments that have been made since the last recalculation, and
+    /// recalculates the rates of the adjustments.
+    fn recalculate(&mut self, raw: Duration) {
+        let elapsed_ns = raw.saturating_sub(self.last_second).as_nanos() as i128;
+        self.last_second = raw;
+
+        let amount_made = |rate_ns: i64, remaining: i64| {
+            let amount = (rate_ns as i128 * elapsed_ns / NSEC_PER_SEC as i128) as i64;
+            if remaining >= 0 {
+                amount.clamp(0, remaining)
+            } else {
+                amount.clamp(remaining, 0)
+            }
+        };
+        self.offset_ns -= amount_made(self.offset_rate_ns, self.offset_ns);
+        self.adjust_ns -= amount_made(self.adjust_rate_ns, self.adjust_ns);
+
+        let elapsed_us = (elapsed_ns / NSEC_PER_USEC as i128) as i64;
+        self.max_error_us += MAX_ERROR_INCREASE_US * elapsed_us / USEC_PER_SEC;
+        if self.max_error_us > NTP_PHASE_LIMIT_US {
+            self.max_error_us = NTP_PHASE_LIMIT_US;
+            self.status |= TimexStatus::STA_UNSYNC;
+        }
+
+        self.offset_rate_ns = shift_right(self.offset_ns, SHIFT_PLL + self.time_constant);
+        self.adjust_rate_ns = self.adjust_ns.clamp(-MAX_TICKADJ_NS, MAX_TICKADJ_NS);
+    }
+
+    /// Advances the leap second state machine.
+    fn update_leap(&mut self, timekeeper: &mut Timekeeper, raw: Duration) {
+        let realtime = timekeeper.unadjusted_realtime_at(raw);
+        match self.state {
+            TimeState::Ok if self.status.contains(TimexStatus::STA_INS) => {
+                self.state = TimeState::Insert;
+                timekeeper.leap = Some(Leap {
+                    is_insertion: true,
+                    at: next_midnight(realtime),
+                });
+            }
+            TimeState::Ok if self.status.contains(TimexStatus::STA_DEL) => {
+                self.state = TimeState::Delete;
+                timekeeper.leap = Some(Leap {
+                    is_insertion: false,
+                    at: next_midnight(realtime) - Duration::from_secs(1),
+                });
+            }
+            TimeState::Insert | TimeState::Delete => {
+                let is_insertion = self.state == TimeState::Insert;
+                let flag = if is_insertion {
+                    TimexStatus::STA_INS
+                } else {
+                    TimexStatus::STA_DEL
+                };
+                let leap = timekeeper.leap.unwrap();
+                if !self.status.contains(flag) {
+                    self.state = TimeState::Ok;
+                    timekeeper.leap = None;
+                } else if realtime >= leap.at {
+                    // Commit the leap second, which the readers have already observed.
+                    timekeeper.leap = None;
+                    if is_insertion {
+                        timekeeper.realtime_offset = timekeeper
+                            .realtime_offset
+                            .saturating_sub(Duration::from_secs(1));
+                        timekeeper.tai_offset += 1;
+                        self.state = TimeState::InProgress;
+                        self.leap_end = leap.at;
+                    } else {
+                        timekeeper.realtime_offset += Duration::from_secs(1);
+                        timekeeper.tai_offset = timekeeper.tai_offset.saturating_sub(1);
+                        self.state = TimeState::Wait;
+                    }
+                }
+            }
+            TimeState::Ok => {
+                timekeeper.leap = None;
+            }
+            TimeState::InProgress if realtime >= self.leap_end => {
+                self.state = TimeState::Wait;
+            }
+            TimeState::Wait
+                if !self
+                    .status
+                    .intersects(TimexStatus::STA_INS | TimexStatus::STA_DEL) =>
+            {
+                self.state = TimeState::Ok;
+            }
+            _ => {}
+        }
+    }
+
+    fn update_status(&mut self, status: TimexStatus, now_secs: u64) {
+        if self.status.contains(TimexStatus::STA_PLL) && !status.contains(TimexStatus::STA_PLL) {
+            self.state = TimeState::Ok;
+            self.status = TimexStatus::STA_UNSYNC;
+        }
+        if !self.status.contains(TimexStatus::STA_PLL) && status.contains(TimexStatus::STA_PLL) {
+            self.ref_time_secs = now_secs;
+        }
+
+        self.status &= TimexStatus::STA_RONLY;
+        self.status |= status - TimexStatus::STA_RONLY;
+    }
+
+    fn update_offset(&mut self, offset: i64, now_secs: u64) {
+        let offset_ns = if self.status.contains(TimexStatus::STA_NANO) {
+            offset
+        } else {
+            offset.saturating_mul(NSEC_PER_USEC)
+        };
+        let offset_ns = offset_ns.clamp(-MAX_PHASE_NS, MAX_PHASE_NS);
+
+        let secs = if self.ref_time_secs == 0 {
+            0
+        } else {
+            now_secs.saturating_sub(self.ref_time_secs) as i64
+        };
+        self.ref_time_secs = now_secs;
+        if self.status.contains(TimexStatus::STA_PLL)
+            && !self.status.contains(TimexStatus::STA_FREQHOLD)
+        {
+            // The frequency error is estimated by the phase offset accumulated in the interval,
+            // like the PLL in Linux.
+            let shift = 2 * (SHIFT_PLL + 2 + self.time_constant);
+            let freq_adj_ns = (offset_ns as i128 * secs as i128) >> shift;
+            let freq_adj_ns = freq_adj_ns.clamp(i64::MIN as i128, i64::MAX as i128) as i64;
+            self.freq = self
+                .freq
+                .saturating_add(nanos_per_sec_to_scaled_ppm(freq_adj_ns))
+                .clamp(-MAX_FREQ_SCALED, MAX_FREQ_SCALED);
+        }
+        self.offset_ns = offset_ns;
+    }
+
+    fn fill(&self, timex: &mut timex_t, realtime: Duration, tai_offset: u32) {
+        let is_nano = self.status.contains(TimexStatus::STA_NANO);
+        timex.freq = self.freq;
+        timex.maxerror = self.max_error_us;
+        timex.esterror = self.est_error_us;
+        timex.status = self.status.bits();
+        timex.constant = self.time_constant;
+        timex.precision = 1;
+        timex.tolerance = MAX_FREQ_SCALED;
+        timex.tick = self.tick_us;
+        timex.tai = tai_offset as i32;
+        timex.time.sec = realtime.as_secs() as i64;
+        timex.time.usec = if is_nano {
+            realtime.subsec_nanos() as i64
+        } else {
+            realtime.subsec_micros() as i64
+        };
+    }
+
+    fn time_state(&self) -> TimeState {
+        if self
+            .status
+            .intersects(TimexStatus::STA_UNSYNC | TimexStatus::STA_CLOCKERR)
+        {
+            TimeState::Error
+        } else {
+            self.state
+        }
+    }
+}
+
+pub(super) fn init() {
+    TIMEKEEPER.write().realtime_offset = *START_TIME_AS_DURATION.get().unwrap();
+    super::softirq::register_callback(update);
+}
+
+/// Recalculates the NTP adjustments and commits the leap second every second.
+///
+/// This function is called in the timer softirq.
+fn update() {
+    let raw = read_monotonic_time();
+    if (raw.as_nanos() as u64) < NEXT_SECOND_NS.load(Ordering::Relaxed) {
+        return;
+    }
+
+    let mut ntp = NTP.lock();
+    if (raw.as_nanos() as u64) < NEXT_SECOND_NS.load(Ordering::Relaxed) {
+        return;
+    }
+    NEXT_SECOND_NS.store(
+        (raw + Duration::from_secs(1)).as_nanos() as u64,
+        Ordering::Relaxed,
+    );
+
+    ntp.recalculate(raw);
+    let mut timekeeper = TIMEKEEPER.write();
+    let old_slewing = (timekeeper.rate, timekeeper.leap.is_some());
+    ntp.update_leap(&mut timekeeper, raw);
+    timekeeper.rebase(raw, ntp.rate());
+    let is_slewing_changed = (timekeeper.rate, timekeeper.leap.is_some()) != old_slewing;
+    drop(timekeeper);
+    drop(ntp);
+
+    if is_slewing_changed {
+        crate::vdso::on_clock_changed();
+    }
+}
+
+/// Reads the times of all the system-wide clocks at the same instant.
+pub fn snapshot() -> TimeSnapshot {
+    let raw = read_monotonic_time();
+    TIMEKEEPER.read().snapshot_at(raw)
+}
+
+/// Returns the times of all the system-wide clocks at a past or future raw monotonic time,
+/// assuming that the current rate of the slewed clocks does not change.
+pub fn snapshot_at(raw: Duration) -> TimeSnapshot {
+    TIMEKEEPER.read().snapshot_at(raw)
+}
+
+/// Returns the time of `CLOCK_MONOTONIC`.
+pub fn monotonic_time() -> Duration {
+    let raw = read_monotonic_time();
+    TIMEKEEPER.read().monotonic_at(raw)
+}
+
+/// Returns the time of `CLOCK_REALTIME`.
+pub fn real_time() -> Duration {
+    snapshot().realtime
+}
+
+/// Returns the time of `CLOCK_TAI`.
+pub fn tai_time() -> Duration {
+    snapshot().tai
+}
+
+/// Adjusts the multiplier that converts the clocksource cycles to the raw monotonic time, so
+/// that it converts the cycles to the slewed monotonic time at the current rate.
+///
+/// It returns `None` if a leap second is pending, in which case the slewed clocks cannot be
+/// calculated from the raw monotonic time with constant offsets.
+pub fn slewed_mult(mult: u32) -> Option<u32> {
+    let timekeeper = TIMEKEEPER.read();
+    if timekeeper.leap.is_some() {
+        return None;
+    }
+
+    // Round down, so that the slewed clocks calculated with the multiplier are never ahead of
+    // the ones read by the kernel.
+    let mult = mult as i128;
+    let adjusted = mult + (mult * timekeeper.rate as i128).div_euclid(SCALED_PPM_PER_UNIT);
+    u32::try_from(adjusted).ok()
+}
+
+/// Sets the time of `CLOCK_REALTIME`.
+///
+/// The real time cannot be earlier than the monotonic time, which is the same as Linux.
+pub fn set_real_time(realtime: Duration) -> Result<()> {
+    let raw = read_monotonic_time();
+    let mut timekeeper = TIMEKEEPER.write();
+    let monotonic = timekeeper.monotonic_at(raw);
+    let Some(offset) = realtime.checked_sub(monotonic) else {
+        return_errno_with_message!(Errno::EINVAL, "the real time is earlier than the boot time");
+    };
+    timekeeper.realtime_offset = offset;
+    drop(timekeeper);
+
+    crate::vdso::on_clock_changed();
+    on_real_time_set();
+    Ok(())
+}
+
+/// Steps the time of `CLOCK_REALTIME` by the offset in nanoseconds.
+fn step_real_time(offset_ns: i128) -> Result<()> {
+    let raw = read_monotonic_time();
+    let mut timekeeper = TIMEKEEPER.write();
+    let monotonic = timekeeper.monotonic_at(raw);
+    let realtime = (monotonic + timekeeper.realtime_offset).as_nanos() as i128 + offset_ns;
+    if realtime < monotonic.as_nanos() as i128 {
+        return_errno_with_message!(Errno::EINVAL, "the real time is earlier than the boot time");
+    }
+    timekeeper.realtime_offset = nanos_to_duration(realtime) - monotonic;
+    drop(timekeeper);
+
+    on_real_time_set();
+    Ok(())
+}
+
+/// Registers a callback that is invoked whenever `CLOCK_REALTIME` is set discontinuously, e.g.,
+/// by `clock_settime(2)` or `adjtimex(2)` with `ADJ_SETOFFSET`.
+///
+/// The callback stays registered until the last strong reference to it is dropped.
+pub fn register_real_time_set_callback(callback: &Arc<dyn Fn() + Send + Sync>) {
+    let mut callbacks = REAL_TIME_SET_CALLBACKS.lock();
+    callbacks.retain(|callback| callback.strong_count() > 0);
+    callbacks.push(Arc::downgrade(callback));
+}
+
+fn on_real_time_set() {
+    let callbacks: Vec<_> = REAL_TIME_SET_CALLBACKS
+        .lock()
+        .iter()
+        .filter_map(Weak::upgrade)
+        .collect();
+    for callback in callbacks {
+        callback();
+    }
+}
+
+/// Reads and adjusts the NTP state, as `adjtimex(2)` does.
+///
+/// Any adjustments other than reading the single-shot adjustment require `can_set_time`.
+pub fn adjtimex(timex: &mut timex_t, can_set_time: bool) -> Result<TimeState> {
+    let modes = TimexModes::from_bits_truncate(timex.modes);
+    let is_adjtime = modes.contains(TimexModes::ADJ_ADJTIME);
+
+    if is_adjtime {
+        if !modes.contains(TimexModes::ADJ_OFFSET) {
+            return_errno_with_message!(Errno::EINVAL, "ADJ_ADJTIME requires ADJ_OFFSET");
+        }
+        if !modes.contains(TimexModes::ADJ_OFFSET_READONLY) && !can_set_time {
+            return_errno_with_message!(Errno::EPERM, "adjusting the time requires CAP_SYS_TIME");
+        }
+    } else {
+        if !modes.is_empty() && !can_set_time {
+            return_errno_with_message!(Errno::EPERM, "adjusting the time requires CAP_SYS_TIME");
+        }
+        if modes.contains(TimexModes::ADJ_TICK)
+            && !(NOMINAL_TICK_US * 9 / 10..=NOMINAL_TICK_US * 11 / 10).contains(&timex.tick)
+        {
+            return_errno_with_message!(Errno::EINVAL, "the tick length is out of range");
+        }
+    }
+
+    if !is_adjtime && modes.contains(TimexModes::ADJ_SETOFFSET) {
+        let (subsec_limit, subsec_scale) = if modes.contains(TimexModes::ADJ_NANO) {
+            (NSEC_PER_SEC, 1)
+        } else {
+            (USEC_PER_SEC, NSEC_PER_USEC)
+        };
+        if !(0..subsec_limit).contains(&timex.time.usec) {
+            return_errno_with_message!(Errno::EINVAL, "the time offset is not normalized");
+        }
+        step_real_time(
+            timex.time.sec as i128 * NSEC_PER_SEC as i128
+                + (timex.time.usec * subsec_scale) as i128,
+        )?;
+    }
+
+    let mut ntp = NTP.lock();
+    let raw = read_monotonic_time();
+    let now_secs = TIMEKEEPER.read().monotonic_at(raw).as_secs();
+    ntp.recalculate(raw);
+
+    if is_adjtime {
+        let old_adjust_us = ntp.adjust_ns / NSEC_PER_USEC;
+        if !modes.contains(TimexModes::ADJ_OFFSET_READONLY) {
+            ntp.adjust_ns = timex.offset.clamp(-MAX_PHASE_NS, MAX_PHASE_NS) * NSEC_PER_USEC;
+        }
+        timex.offset = old_adjust_us;
+    } else {
+        if modes.contains(TimexModes::ADJ_STATUS) {
+            ntp.update_status(TimexStatus::from_bits_truncate(timex.status), now_secs);
+        }
+        if modes.contains(TimexModes::ADJ_NANO) {
+            ntp.status |= TimexStatus::STA_NANO;
+        }
+        if modes.contains(TimexModes::ADJ_MICRO) {
+            ntp.status -= TimexStatus::STA_NANO;
+        }
+        if modes.contains(TimexModes::ADJ_FREQUENCY) {
+            ntp.freq = timex.freq.clamp(-MAX_FREQ_SCALED, MAX_FREQ_SCALED);
+        }
+        if modes.contains(TimexModes::ADJ_MAXERROR) {
+            ntp.max_error_us = timex.maxerror.clamp(0, NTP_PHASE_LIMIT_US);
+        }
+        if modes.contains(TimexModes::ADJ_ESTERROR) {
+            ntp.est_error_us = timex.esterror.clamp(0, NTP_PHASE_LIMIT_US);
+        }
+        if modes.contains(TimexModes::ADJ_TIMECONST) {
+            let time_constant = if ntp.status.contains(TimexStatus::STA_NANO) {
+                timex.constant
+            } else {
+                timex.constant.saturating_add(4)
+            };
+            ntp.time_constant = time_constant.clamp(0, MAX_TIME_CONSTANT);
+        }
+        if modes.contains(TimexModes::ADJ_TAI) && (0..=MAX_TAI_OFFSET).contains(&timex.constant) {
+            TIMEKEEPER.write().tai_offset = timex.constant as u32;
+        }
+        if modes.contains(TimexModes::ADJ_OFFSET) {
+            ntp.update_offset(timex.offset, now_secs);
+        }
+        if modes.contains(TimexModes::ADJ_TICK) {
+            ntp.tick_us = timex.tick;
+        }
+
+        timex.offset = if ntp.status.contains(TimexStatus::STA_NANO) {
+            ntp.offset_ns
+        } else {
+            ntp.offset_ns / NSEC_PER_USEC
+        };
+    }
+
+    // Make the adjustments take effect immediately.
+    ntp.recalculate(raw);
+    let mut timekeeper = TIMEKEEPER.write();
+    ntp.update_leap(&mut timekeeper, raw);
+    timekeeper.rebase(raw, ntp.rate());
+    let snapshot = timekeeper.snapshot_at(raw);
+    let tai_offset = timekeeper.tai_offset;
+    drop(timekeeper);
+
+    ntp.fill(timex, snapshot.realtime, tai_offset);
+    let state = ntp.time_state();
+    drop(ntp);
+
+    crate::vdso::on_clock_changed();
+    Ok(state)
+}
+
+fn next_midnight(realtime: Duration) -> Duration {
+    Duration::from_secs((realtime.as_secs() / SECS_PER_DAY + 1) * SECS_PER_DAY)
+}
+
+fn nanos_per_sec_to_scaled_ppm(nanos: i64) -> i64 {
+    // One nanosecond per second is 1/1000 ppm.
+    (((nanos as i128) << 16) / 1000) as i64
+}
+
+/// Shifts the value right, rounding towards zero like Linux does for the phase offsets.
+fn shift_right(value: i64, shift: i64) -> i64 {
+    if value < 0 {
+        -((-value) >> shift)
+    } else {
+        value >> shift
+    }
+}
+
+fn nanos_to_duration(nanos: i128) -> Duration {
+    let nanos = nanos.max(0) as u128;
+    Duration::new(
+        (nanos / NSEC_PER_SEC as u128) as u64,
+        (nanos % NSEC_PER_SEC as u128) as u32,
+    )
+}
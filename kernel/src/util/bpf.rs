@@ -0,0 +1,347 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! Programs in the classic BPF format.
+//!
+//! Classic BPF programs are used by socket filters and seccomp filters. This module validates and
+//! interprets the programs, while the users decide which loads from the input data are allowed
+//! and what the input data are.
+//!
+//! For more details, see <https://www.kernel.org/doc/html/v6.0/networking/filter.html>.
+
+use crate::{current_userspace, prelude::*};
+
+/// An instruction of the classic BPF (`struct sock_filter`).
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Pod)]
+pub struct CSockFilter {
+    /// The opcode
+    pub code: u16,
+    /// The jump offset if the condition is true
+    pub jt: u8,
+    /// The jump offset if the condition is false
+    pub jf: u8,
+    /// Generic multiuse field
+    pub k: u32,
+}
+
+/// A classic BPF program in the user space (`struct sock_fprog`).
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Pod)]
+pub struct CSockFprog {
+    /// The number of instructions
+    len: u16,
+    _pad: [u8; 6],
+    /// The pointer to the instructions
+    filter: u64,
+}
+
+impl CSockFprog {
+    /// Reads the instructions of the program from the user space.
+    ///
+    /// This method fails with `EINVAL` if the program has more than [`BpfProgram::MAX_LEN`]
+    /// instructions.
+    pub fn read_insns(&self) -> Result<Vec<CSockFilter>> {
+        if self.len as usize > BpfProgram::MAX_LEN {
+            return_errno_with_message!(Errno::EINVAL, "the filter is too long");
+        }
+
+        let user_space = current_userspace!();
+        (0..self.len as usize)
+            .map(|i| {
+                user_space.read_val::<CSockFilter>(
+                    self.filter as Vaddr + i * core::mem::size_of::<CSockFilter>(),
+                )
+            })
+            .collect()
+    }
+}
+
+/// The number of words in the scratch memory.
+const BPF_MEMWORDS: usize = 16;
+
+// Instruction classes.
+pub const BPF_LD: u16 = 0x00;
+pub const BPF_LDX: u16 = 0x01;
+pub const BPF_ST: u16 = 0x02;
+pub const BPF_STX: u16 = 0x03;
+pub const BPF_ALU: u16 = 0x04;
+pub const BPF_JMP: u16 = 0x05;
+pub const BPF_RET: u16 = 0x06;
+pub const BPF_MISC: u16 = 0x07;
+
+// Sizes of the load instructions.
+pub const BPF_W: u16 = 0x00;
+pub const BPF_H: u16 = 0x08;
+pub const BPF_B: u16 = 0x10;
+
+// Modes of the load instructions.
+pub const BPF_IMM: u16 = 0x00;
+pub const BPF_ABS: u16 = 0x20;
+pub const BPF_IND: u16 = 0x40;
+pub const BPF_MEM: u16 = 0x60;
+pub const BPF_LEN: u16 = 0x80;
+pub const BPF_MSH: u16 = 0xa0;
+
+// Operations of the ALU and jump instructions.
+pub const BPF_ADD: u16 = 0x00;
+pub const BPF_SUB: u16 = 0x10;
+pub const BPF_MUL: u16 = 0x20;
+pub const BPF_DIV: u16 = 0x30;
+pub const BPF_OR: u16 = 0x40;
+pub const BPF_AND: u16 = 0x50;
+pub const BPF_LSH: u16 = 0x60;
+pub const BPF_RSH: u16 = 0x70;
+pub const BPF_NEG: u16 = 0x80;
+pub const BPF_MOD: u16 = 0x90;
+pub const BPF_XOR: u16 = 0xa0;
+pub const BPF_JA: u16 = 0x00;
+pub const BPF_JEQ: u16 = 0x10;
+pub const BPF_JGT: u16 = 0x20;
+pub const BPF_JGE: u16 = 0x30;
+pub const BPF_JSET: u16 = 0x40;
+
+// Sources of the ALU and jump instructions.
+pub const BPF_K: u16 = 0x00;
+pub const BPF_X: u16 = 0x08;
+
+// Sources of the return instructions.
+pub const BPF_A: u16 = 0x10;
+
+// Operations of the miscellaneous instructions.
+pub const BPF_TAX: u16 = 0x00;
+pub const BPF_TXA: u16 = 0x80;
+
+/// The input data that a BPF program runs against.
+pub trait BpfInput {
+    /// Returns the length of the data in bytes, which is loaded by `BPF_LEN`.
+    fn data_len(&self) -> u32;
+
+    /// Loads a value of `size` bytes at `offset` of the data.
+    ///
+    /// Returning `None` aborts the program, which then returns zero.
+    fn load(&self, offset: u32, size: usize) -> Option<u32>;
+
+    /// Loads a value of `size` bytes at the constant `offset` of the data (i.e., `BPF_ABS`).
+    ///
+    /// This is the same as [`Self::load`] by default. Implementations can override this to
+    /// provide extra data at special offsets.
+    fn load_abs(&self, offset: u32, size: usize) -> Option<u32> {
+        self.load(offset, size)
+    }
+}
+
+/// A validated BPF program.
+#[derive(Debug, Clone)]
+pub struct BpfProgram {
+    insns: Arc<[CSockFilter]>,
+}
+
+impl BpfProgram {
+    /// The maximum number of instructions in a program.
+    pub const MAX_LEN: usize = 4096;
+
+    /// Creates a program from the instructions.
+    ///
+    /// This method fails with `EINVAL` if the program is invalid. Similar to Linux, a valid
+    /// program must not be empty or too long, must only contain known instructions, must not
+    /// jump out of the program and must end with a return instruction. In addition, every load
+    /// instruction that reads the input data (i.e., `BPF_ABS`, `BPF_IND`, and `BPF_MSH`) must be
+    /// accepted by `is_load_valid`.
+    pub fn new(
+        insns: Vec<CSockFilter>,
+        is_load_valid: impl Fn(&CSockFilter) -> bool,
+    ) -> Result<Self> {
+        if insns.is_empty() || insns.len() > Self::MAX_LEN {
+            return_errno_with_message!(Errno::EINVAL, "the filter length is invalid");
+        }
+
+        for (pc, insn) in insns.iter().enumerate() {
+            check_insn(insn, insns.len() - pc - 1, &is_load_valid)?;
+        }
+
+        if insns.last().unwrap().code & 0x07 != BPF_RET {
+            return_errno_with_message!(
+                Errno::EINVAL,
+                "the filter does not end with a return instruction"
+            );
+        }
+
+        Ok(Self {
+            insns: insns.into(),
+        })
+    }
+
+    /// Returns the number of instructions in the program.
+    pub fn len(&self) -> usize {
+        self.insns.len()
+    }
+
+    /// Runs the program against the input data and returns the result.
+    pub fn run(&self, input: &impl BpfInput) -> u32 {
+        let mut a: u32 = 0;
+        let mut x: u32 = 0;
+        let mut mem = [0u32; BPF_MEMWORDS];
+
+        let mut pc = 0;
+        loop {
+            let insn = &self.insns[pc];
+            pc += 1;
+
+            let k = insn.k;
+            let src = if insn.code & BPF_X != 0 { x } else { k };
+
+            match insn.code & 0x07 {
+                BPF_LD => {
+                    a = match insn.code & 0xe0 {
+                        BPF_IMM => k,
+                        BPF_MEM => mem[k as usize],
+                        BPF_LEN => input.data_len(),
+                        mode => {
+                            let size = load_size(insn.code);
+                            let val = if mode == BPF_IND {
+                                input.load(x.wrapping_add(k), size)
+                            } else {
+                                input.load_abs(k, size)
+                            };
+                            match val {
+                                Some(val) => val,
+                                None => return 0,
+                            }
+                        }
+                    }
+                }
+                BPF_LDX => {
+                    x = match insn.code & 0xe0 {
+                        BPF_IMM => k,
+                        BPF_MEM => mem[k as usize],
+                        BPF_LEN => input.data_len(),
+                        _ => match input.load(k, 1) {
+                            // BPF_MSH: Loads the IPv4 header length.
+                            Some(byte) => (byte & 0xf) << 2,
+                            None => return 0,
+                        },
+                    }
+                }
+                BPF_ST => mem[k as usize] = a,
+                BPF_STX => mem[k as usize] = x,
+                BPF_ALU => {
+                    a = match insn.code & 0xf0 {
+                        BPF_ADD => a.wrapping_add(src),
+                        BPF_SUB => a.wrapping_sub(src),
+                        BPF_MUL => a.wrapping_mul(src),
+                        BPF_DIV | BPF_MOD if src == 0 => return 0,
+                        BPF_DIV => a / src,
+                        BPF_MOD => a % src,
+                        BPF_OR => a | src,
+                        BPF_AND => a & src,
+                        BPF_LSH => a.wrapping_shl(src),
+                        BPF_RSH => a.wrapping_shr(src),
+                        BPF_NEG => a.wrapping_neg(),
+                        _ => a ^ src,
+                    }
+                }
+                BPF_JMP => {
+                    let is_taken = match insn.code & 0xf0 {
+                        BPF_JA => {
+                            pc += k as usize;
+                            continue;
+                        }
+                        BPF_JEQ => a == src,
+                        BPF_JGT => a > src,
+                        BPF_JGE => a >= src,
+                        _ => a & src != 0,
+                    };
+                    pc += if is_taken { insn.jt } else { insn.jf } as usize;
+                }
+                BPF_RET => {
+                    return if insn.code & BPF_A != 0 { a } else { k };
+                }
+                _ => {
+                    if insn.code & 0xf8 == BPF_TXA {
+                        a = x;
+                    } else {
+                        x = a;
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Returns the number of bytes that a load instruction reads.
+fn load_size(code: u16) -> usize {
+    match code & 0x18 {
+        BPF_W => 4,
+        BPF_H => 2,
+        _ => 1,
+    }
+}
+
+/// Checks whether the instruction is valid.
+///
+/// The number of the instructions following this instruction must be specified as `num_after`,
+/// to check whether the jump targets are valid.
+fn check_insn(
+    insn: &CSockFilter,
+    num_after: usize,
+    is_load_valid: &impl Fn(&CSockFilter) -> bool,
+) -> Result<()> {
+    const ALU_OPS: [u16; 10] = [
+        BPF_ADD, BPF_SUB, BPF_MUL, BPF_DIV, BPF_OR, BPF_AND, BPF_LSH, BPF_RSH, BPF_MOD, BPF_XOR,
+    ];
+    const JMP_OPS: [u16; 4] = [BPF_JEQ, BPF_JGT, BPF_JGE, BPF_JSET];
+
+    let code = insn.code;
+    let k = insn.k;
+    let is_mem_valid = (k as usize) < BPF_MEMWORDS;
+
+    let is_valid = match code {
+        // Load instructions
+        _ if code == BPF_LD | BPF_W | BPF_MEM || code == BPF_LDX | BPF_W | BPF_MEM => is_mem_valid,
+        _ if [BPF_W, BPF_H, BPF_B]
+            .iter()
+            .any(|size| code == BPF_LD | size | BPF_ABS || code == BPF_LD | size | BPF_IND) =>
+        {
+            is_load_valid(insn)
+        }
+        _ if code == BPF_LDX | BPF_B | BPF_MSH => is_load_valid(insn),
+        _ if code == BPF_LD | BPF_W | BPF_IMM
+            || code == BPF_LD | BPF_W | BPF_LEN
+            || code == BPF_LDX | BPF_W | BPF_IMM
+            || code == BPF_LDX | BPF_W | BPF_LEN =>
+        {
+            true
+        }
+        // Store instructions
+        BPF_ST | BPF_STX => is_mem_valid,
+        // ALU instructions
+        _ if code == BPF_ALU | BPF_NEG => true,
+        _ if code == BPF_ALU | BPF_DIV | BPF_K || code == BPF_ALU | BPF_MOD | BPF_K => k != 0,
+        _ if code == BPF_ALU | BPF_LSH | BPF_K || code == BPF_ALU | BPF_RSH | BPF_K => k < 32,
+        _ if ALU_OPS
+            .iter()
+            .any(|op| code == BPF_ALU | op | BPF_K || code == BPF_ALU | op | BPF_X) =>
+        {
+            true
+        }
+        // Jump instructions
+        _ if code == BPF_JMP | BPF_JA => (k as usize) < num_after,
+        _ if JMP_OPS
+            .iter()
+            .any(|op| code == BPF_JMP | op | BPF_K || code == BPF_JMP | op | BPF_X) =>
+        {
+            (insn.jt as usize) < num_after && (insn.jf as usize) < num_after
+        }
+        // Return instructions
+        _ if code == BPF_RET | BPF_K || code == BPF_RET | BPF_A => true,
+        // Miscellaneous instructions
+        _ if code == BPF_MISC | BPF_TAX || code == BPF_MISC | BPF_TXA => true,
+        _ => false,
+    };
+
+    if !is_valid {
+        return_errno_with_message!(Errno::EINVAL, "the filter contains an invalid instruction");
+    }
+
+    Ok(())
+}
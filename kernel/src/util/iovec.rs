@@ -1,6 +1,6 @@
 // SPDX-License-Identifier: MPL-2.0
 
-use ostd::mm::{Infallible, VmSpace};
+use ostd::mm::Infallible;
 
 use crate::prelude::*;
 
@@ -38,45 +38,71 @@ impl TryFrom<UserIoVec> for IoVec {
     }
 }
 
+/// A user space IO vector of 32-bit programs.
+#[cfg(target_arch = "x86_64")]
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Pod)]
+struct CompatUserIoVec {
+    base: u32,
+    len: i32,
+}
+
+#[cfg(target_arch = "x86_64")]
+impl TryFrom<CompatUserIoVec> for IoVec {
+    type Error = Error;
+
+    fn try_from(value: CompatUserIoVec) -> Result<Self> {
+        if value.len < 0 {
+            return_errno_with_message!(Errno::EINVAL, "the length of IO vector cannot be negative");
+        }
+
+        Ok(IoVec {
+            base: value.base as Vaddr,
+            len: value.len as usize,
+        })
+    }
+}
+
 impl IoVec {
     /// Returns whether the `IoVec` points to an empty user buffer.
     const fn is_empty(&self) -> bool {
         self.len == 0 || self.base == 0
     }
 
-    fn reader<'a>(&self, vm_space: &'a VmSpace) -> Result<VmReader<'a>> {
-        Ok(vm_space.reader(self.base, self.len)?)
+    fn reader<'a>(&self, user_space: &'a CurrentUserSpace) -> Result<VmReader<'a>> {
+        user_space.reader(self.base, self.len)
     }
 
-    fn writer<'a>(&self, vm_space: &'a VmSpace) -> Result<VmWriter<'a>> {
-        Ok(vm_space.writer(self.base, self.len)?)
+    fn writer<'a>(&self, user_space: &'a CurrentUserSpace) -> Result<VmWriter<'a>> {
+        user_space.writer(self.base, self.len)
     }
 }
 
 /// The util function for create [`VmReader`]/[`VmWriter`]s.
-fn copy_iovs_and_convert<'a, T: 'a>(
-    ctx: &'a Context,
+///
+/// The user space IO vectors are of type `U`, which differs between the native and the 32-bit
+/// programs.
+fn copy_iovs_and_convert<'a, T: 'a, U: Pod + TryInto<IoVec, Error = Error>>(
+    user_space: &'a CurrentUserSpace,
     start_addr: Vaddr,
     count: usize,
-    convert_iovec: impl Fn(&IoVec, &'a VmSpace) -> Result<T>,
+    convert_iovec: impl Fn(&IoVec, &'a CurrentUserSpace) -> Result<T>,
 ) -> Result<Box<[T]>> {
-    let vm_space = ctx.process.root_vmar().vm_space();
-
     let mut v = Vec::with_capacity(count);
     for idx in 0..count {
         let iov = {
-            let addr = start_addr + idx * core::mem::size_of::<UserIoVec>();
-            let uiov: UserIoVec = vm_space
-                .reader(addr, core::mem::size_of::<UserIoVec>())?
+            let addr = start_addr + idx * core::mem::size_of::<U>();
+            let uiov: U = user_space
+                .reader(addr, core::mem::size_of::<U>())?
                 .read_val()?;
-            IoVec::try_from(uiov)?
+            uiov.try_into()?
         };
 
         if iov.is_empty() {
             continue;
         }
 
-        let converted = convert_iovec(&iov, vm_space)?;
+        let converted = convert_iovec(&iov, user_space)?;
         v.push(converted)
     }
 
@@ -96,11 +122,28 @@ pub struct VmWriterArray<'a>(Box<[VmWriter<'a>]>);
 impl<'a> VmReaderArray<'a> {
     /// Creates a new `IoVecReader` from user-provided io vec buffer.
     pub fn from_user_io_vecs(
-        ctx: &'a Context<'a>,
+        user_space: &'a CurrentUserSpace,
         start_addr: Vaddr,
         count: usize,
     ) -> Result<Self> {
-        let readers = copy_iovs_and_convert(ctx, start_addr, count, IoVec::reader)?;
+        let readers =
+            copy_iovs_and_convert::<_, UserIoVec>(user_space, start_addr, count, IoVec::reader)?;
+        Ok(Self(readers))
+    }
+
+    /// Creates a new `IoVecReader` from the io vec buffer of a 32-bit program.
+    #[cfg(target_arch = "x86_64")]
+    pub fn from_user_compat_io_vecs(
+        user_space: &'a CurrentUserSpace,
+        start_addr: Vaddr,
+        count: usize,
+    ) -> Result<Self> {
+        let readers = copy_iovs_and_convert::<_, CompatUserIoVec>(
+            user_space,
+            start_addr,
+            count,
+            IoVec::reader,
+        )?;
         Ok(Self(readers))
     }
 
@@ -113,11 +156,28 @@ impl<'a> VmReaderArray<'a> {
 impl<'a> VmWriterArray<'a> {
     /// Creates a new `IoVecWriter` from user-provided io vec buffer.
     pub fn from_user_io_vecs(
-        ctx: &'a Context<'a>,
+        user_space: &'a CurrentUserSpace,
+        start_addr: Vaddr,
+        count: usize,
+    ) -> Result<Self> {
+        let writers =
+            copy_iovs_and_convert::<_, UserIoVec>(user_space, start_addr, count, IoVec::writer)?;
+        Ok(Self(writers))
+    }
+
+    /// Creates a new `IoVecWriter` from the io vec buffer of a 32-bit program.
+    #[cfg(target_arch = "x86_64")]
+    pub fn from_user_compat_io_vecs(
+        user_space: &'a CurrentUserSpace,
         start_addr: Vaddr,
         count: usize,
     ) -> Result<Self> {
-        let writers = copy_iovs_and_convert(ctx, start_addr, count, IoVec::writer)?;
+        let writers = copy_iovs_and_convert::<_, CompatUserIoVec>(
+            user_space,
+            start_addr,
+            count,
+            IoVec::writer,
+        )?;
         Ok(Self(writers))
     }
 
@@ -0,0 +1,74 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! Hiding the kernel addresses from unprivileged users.
+//!
+//! Like `kptr_restrict` of Linux, the policy is controlled by `/proc/sys/kernel/kptr_restrict`:
+//! - `0`: The kernel addresses are shown to all users.
+//! - `1`: The kernel addresses are shown only to the users with `CAP_SYSLOG`.
+//! - `2`: The kernel addresses are hidden from all users.
+//!
+//! The hidden addresses are shown as zeros.
+
+use core::{
+    fmt,
+    sync::atomic::{AtomicU8, Ordering},
+};
+
+use crate::{
+    prelude::*,
+    process::{credentials::capabilities::CapSet, posix_thread::AsPosixThread},
+};
+
+/// The maximum value of the policy.
+pub const MAX_KPTR_RESTRICT: u8 = 2;
+
+static KPTR_RESTRICT: AtomicU8 = AtomicU8::new(0);
+
+/// Returns the current policy.
+pub fn kptr_restrict() -> u8 {
+    KPTR_RESTRICT.load(Ordering::Relaxed)
+}
+
+/// Sets the policy.
+pub fn set_kptr_restrict(value: u8) -> Result<()> {
+    if value > MAX_KPTR_RESTRICT {
+        return_errno_with_message!(Errno::EINVAL, "the policy is invalid");
+    }
+    KPTR_RESTRICT.store(value, Ordering::Relaxed);
+    Ok(())
+}
+
+/// Returns whether the kernel addresses can be shown to the current thread.
+pub fn can_show_kernel_ptrs() -> bool {
+    match kptr_restrict() {
+        0 => true,
+        1 => {
+            let current = current_thread!();
+            let Some(posix_thread) = current.as_posix_thread() else {
+                return false;
+            };
+            posix_thread
+                .credentials()
+                .effective_capset()
+                .contains(CapSet::SYSLOG)
+        }
+        _ => false,
+    }
+}
+
+/// A kernel address to be shown to the current thread.
+///
+/// It is formatted in hexadecimal like `{:#x}`, or as zero if the address should be hidden
+/// from the current thread according to the policy.
+#[derive(Debug, Clone, Copy)]
+pub struct KernelPtr(pub Vaddr);
+
+impl fmt::Display for KernelPtr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if can_show_kernel_ptrs() {
+            write!(f, "{:#x}", self.0)
+        } else {
+            write!(f, "{:#x}", 0)
+        }
+    }
+}
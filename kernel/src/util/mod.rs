@@ -1,6 +1,8 @@
 // SPDX-License-Identifier: MPL-2.0
 
+pub mod bpf;
 mod iovec;
+pub mod kptr;
 pub mod net;
 pub mod random;
 pub mod ring_buffer;
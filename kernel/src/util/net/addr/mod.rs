@@ -7,5 +7,7 @@ pub use family::{
 
 mod family;
 mod ip;
+mod netlink;
+mod packet;
 mod unix;
 mod vsock;
@@ -0,0 +1,37 @@
+// SPDX-License-Identifier: MPL-2.0
+
+use super::family::CSocketAddrFamily;
+use crate::{net::socket::netlink::NetlinkSocketAddr, prelude::*};
+
+/// Netlink socket address.
+///
+/// See <https://www.man7.org/linux/man-pages/man7/netlink.7.html>.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Pod)]
+pub(super) struct CSocketAddrNetlink {
+    /// Address family (AF_NETLINK).
+    nl_family: u16,
+    /// Pad bytes (always zero).
+    nl_pad: u16,
+    /// Port ID.
+    nl_pid: u32,
+    /// Multicast groups mask.
+    nl_groups: u32,
+}
+
+impl From<NetlinkSocketAddr> for CSocketAddrNetlink {
+    fn from(value: NetlinkSocketAddr) -> Self {
+        Self {
+            nl_family: CSocketAddrFamily::AF_NETLINK as u16,
+            nl_pad: 0,
+            nl_pid: value.port(),
+            nl_groups: value.groups(),
+        }
+    }
+}
+
+impl From<CSocketAddrNetlink> for NetlinkSocketAddr {
+    fn from(value: CSocketAddrNetlink) -> Self {
+        Self::new(value.nl_pid, value.nl_groups)
+    }
+}
@@ -0,0 +1,53 @@
+// SPDX-License-Identifier: MPL-2.0
+
+use super::family::CSocketAddrFamily;
+use crate::{net::socket::packet::PacketSocketAddr, prelude::*};
+
+/// Link-layer socket address.
+///
+/// See <https://www.man7.org/linux/man-pages/man7/packet.7.html>.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Pod)]
+pub(super) struct CSocketAddrLinkLayer {
+    /// Address family (AF_PACKET).
+    sll_family: u16,
+    /// Physical-layer protocol in network byte order.
+    sll_protocol: u16,
+    /// Interface index.
+    sll_ifindex: i32,
+    /// ARP hardware type.
+    sll_hatype: u16,
+    /// Packet type.
+    sll_pkttype: u8,
+    /// Length of address.
+    sll_halen: u8,
+    /// Physical-layer address.
+    sll_addr: [u8; 8],
+}
+
+impl From<PacketSocketAddr> for CSocketAddrLinkLayer {
+    fn from(value: PacketSocketAddr) -> Self {
+        Self {
+            sll_family: CSocketAddrFamily::AF_PACKET as u16,
+            sll_protocol: value.protocol.to_be(),
+            sll_ifindex: value.ifindex as i32,
+            sll_hatype: value.hardware_type,
+            sll_pkttype: value.packet_type,
+            sll_halen: value.hardware_addr_len,
+            sll_addr: value.hardware_addr,
+        }
+    }
+}
+
+impl From<CSocketAddrLinkLayer> for PacketSocketAddr {
+    fn from(value: CSocketAddrLinkLayer) -> Self {
+        Self {
+            protocol: u16::from_be(value.sll_protocol),
+            ifindex: value.sll_ifindex as u32,
+            hardware_type: value.sll_hatype,
+            packet_type: value.sll_pkttype,
+            hardware_addr_len: value.sll_halen,
+            hardware_addr: value.sll_addr,
+        }
+    }
+}
@@ -0,0 +1,57 @@
+// SPDX-License-Identifier: MPL-2.0
+
+use super::RawSocketOption;
+use crate::{
+    impl_raw_sock_option_set_only, impl_raw_socket_option,
+    net::socket::ip::options::{
+        AddMembership, DropMembership, HdrIncl, MulticastIf, MulticastLoop, MulticastTtl, RecvTtl,
+        Ttl,
+    },
+    prelude::*,
+    util::net::options::SocketOption,
+};
+
+/// Sock options for IPv4 sockets.
+///
+/// The raw definition is from https://elixir.bootlin.com/linux/v6.0.9/source/include/uapi/linux/in.h#L94
+#[repr(i32)]
+#[derive(Debug, Clone, Copy, TryFromInt)]
+#[allow(non_camel_case_types)]
+#[allow(clippy::upper_case_acronyms)]
+pub enum CIpOptionName {
+    TOS = 1,              /* Type of service */
+    TTL = 2,              /* Time to live */
+    HDRINCL = 3,          /* Header is included with data */
+    OPTIONS = 4,          /* IP options */
+    RECVOPTS = 6,         /* Receive all IP options with datagram */
+    RECVTTL = 12,         /* Receive the TTL with datagram */
+    MULTICAST_IF = 32,    /* The iface to send multicast packets */
+    MULTICAST_TTL = 33,   /* The TTL of multicast packets */
+    MULTICAST_LOOP = 34,  /* Whether to loop back multicast packets */
+    ADD_MEMBERSHIP = 35,  /* Join a multicast group */
+    DROP_MEMBERSHIP = 36, /* Leave a multicast group */
+}
+
+pub fn new_ip_option(name: i32) -> Result<Box<dyn RawSocketOption>> {
+    let name = CIpOptionName::try_from(name).map_err(|_| Errno::ENOPROTOOPT)?;
+    match name {
+        CIpOptionName::TTL => Ok(Box::new(Ttl::new())),
+        CIpOptionName::HDRINCL => Ok(Box::new(HdrIncl::new())),
+        CIpOptionName::RECVTTL => Ok(Box::new(RecvTtl::new())),
+        CIpOptionName::MULTICAST_IF => Ok(Box::new(MulticastIf::new())),
+        CIpOptionName::MULTICAST_TTL => Ok(Box::new(MulticastTtl::new())),
+        CIpOptionName::MULTICAST_LOOP => Ok(Box::new(MulticastLoop::new())),
+        CIpOptionName::ADD_MEMBERSHIP => Ok(Box::new(AddMembership::new())),
+        CIpOptionName::DROP_MEMBERSHIP => Ok(Box::new(DropMembership::new())),
+        _ => return_errno_with_message!(Errno::ENOPROTOOPT, "unsupported ip-level option"),
+    }
+}
+
+impl_raw_socket_option!(Ttl);
+impl_raw_socket_option!(HdrIncl);
+impl_raw_socket_option!(RecvTtl);
+impl_raw_socket_option!(MulticastIf);
+impl_raw_socket_option!(MulticastTtl);
+impl_raw_socket_option!(MulticastLoop);
+impl_raw_sock_option_set_only!(AddMembership);
+impl_raw_sock_option_set_only!(DropMembership);
@@ -0,0 +1,36 @@
+// SPDX-License-Identifier: MPL-2.0
+
+use super::RawSocketOption;
+use crate::{
+    impl_raw_socket_option,
+    net::socket::ip::options::{RecvHopLimit, UnicastHops},
+    prelude::*,
+    util::net::options::SocketOption,
+};
+
+/// Sock options for IPv6 sockets.
+///
+/// The raw definition is from https://elixir.bootlin.com/linux/v6.0.9/source/include/uapi/linux/in6.h#L163
+#[repr(i32)]
+#[derive(Debug, Clone, Copy, TryFromInt)]
+#[allow(non_camel_case_types)]
+#[allow(clippy::upper_case_acronyms)]
+pub enum CIpv6OptionName {
+    CHECKSUM = 7,      /* Offset of the checksum */
+    UNICAST_HOPS = 16, /* Hop limit of unicast packets */
+    V6ONLY = 26,       /* Restrict to IPv6 packets only */
+    RECVPKTINFO = 49,  /* Receive the destination address and the incoming iface */
+    RECVHOPLIMIT = 51, /* Receive the hop limit with datagram */
+}
+
+pub fn new_ipv6_option(name: i32) -> Result<Box<dyn RawSocketOption>> {
+    let name = CIpv6OptionName::try_from(name).map_err(|_| Errno::ENOPROTOOPT)?;
+    match name {
+        CIpv6OptionName::UNICAST_HOPS => Ok(Box::new(UnicastHops::new())),
+        CIpv6OptionName::RECVHOPLIMIT => Ok(Box::new(RecvHopLimit::new())),
+        _ => return_errno_with_message!(Errno::ENOPROTOOPT, "unsupported ipv6-level option"),
+    }
+}
+
+impl_raw_socket_option!(UnicastHops);
+impl_raw_socket_option!(RecvHopLimit);
@@ -0,0 +1,48 @@
+// SPDX-License-Identifier: MPL-2.0
+
+use super::RawSocketOption;
+use crate::{
+    impl_raw_socket_option,
+    net::socket::ip::raw::options::{Icmp6Filter, IcmpFilter},
+    prelude::*,
+    util::net::options::SocketOption,
+};
+
+/// Sock options for raw sockets.
+///
+/// The raw definition is from https://elixir.bootlin.com/linux/v6.0.9/source/include/uapi/linux/icmp.h#L87
+#[repr(i32)]
+#[derive(Debug, Clone, Copy, TryFromInt)]
+#[allow(non_camel_case_types)]
+#[allow(clippy::upper_case_acronyms)]
+pub enum CRawOptionName {
+    FILTER = 1, /* Filter of ICMP types */
+}
+
+/// Sock options for ICMPv6 raw sockets.
+///
+/// The raw definition is from https://elixir.bootlin.com/linux/v6.0.9/source/include/uapi/linux/icmpv6.h#L144
+#[repr(i32)]
+#[derive(Debug, Clone, Copy, TryFromInt)]
+#[allow(non_camel_case_types)]
+#[allow(clippy::upper_case_acronyms)]
+pub enum CIcmpv6OptionName {
+    FILTER = 1, /* Filter of ICMPv6 types */
+}
+
+pub fn new_raw_option(name: i32) -> Result<Box<dyn RawSocketOption>> {
+    let name = CRawOptionName::try_from(name).map_err(|_| Errno::ENOPROTOOPT)?;
+    match name {
+        CRawOptionName::FILTER => Ok(Box::new(IcmpFilter::new())),
+    }
+}
+
+pub fn new_icmpv6_option(name: i32) -> Result<Box<dyn RawSocketOption>> {
+    let name = CIcmpv6OptionName::try_from(name).map_err(|_| Errno::ENOPROTOOPT)?;
+    match name {
+        CIcmpv6OptionName::FILTER => Ok(Box::new(Icmp6Filter::new())),
+    }
+}
+
+impl_raw_socket_option!(IcmpFilter);
+impl_raw_socket_option!(Icmp6Filter);
@@ -3,7 +3,9 @@
 use super::RawSocketOption;
 use crate::{
     impl_raw_socket_option,
-    net::socket::ip::stream::options::{Congestion, KeepIdle, MaxSegment, NoDelay, WindowClamp},
+    net::socket::ip::stream::options::{
+        Congestion, KeepCount, KeepIdle, KeepInterval, MaxSegment, NoDelay, WindowClamp,
+    },
     prelude::*,
     util::net::options::SocketOption,
 };
@@ -20,7 +22,8 @@ pub enum CTcpOptionName {
     MAXSEG = 2,        /* Limit MSS */
     CORK = 3,          /* Never send partially complete segments */
     KEEPIDLE = 4,      /* Start keeplives after this period */
-    KEEPALIVE = 5,     /* Interval between keepalives */
+    KEEPINTVL = 5,     /* Interval between keepalives */
+    KEEPCNT = 6,       /* Number of keepalives before death */
     WINDOW_CLAMP = 10, /* Bound advertised window */
     CONGESTION = 13,   /* Congestion control algorithm */
 }
@@ -31,6 +34,8 @@ pub fn new_tcp_option(name: i32) -> Result<Box<dyn RawSocketOption>> {
         CTcpOptionName::NODELAY => Ok(Box::new(NoDelay::new())),
         CTcpOptionName::MAXSEG => Ok(Box::new(MaxSegment::new())),
         CTcpOptionName::KEEPIDLE => Ok(Box::new(KeepIdle::new())),
+        CTcpOptionName::KEEPINTVL => Ok(Box::new(KeepInterval::new())),
+        CTcpOptionName::KEEPCNT => Ok(Box::new(KeepCount::new())),
         CTcpOptionName::WINDOW_CLAMP => Ok(Box::new(WindowClamp::new())),
         CTcpOptionName::CONGESTION => Ok(Box::new(Congestion::new())),
         _ => return_errno_with_message!(Errno::ENOPROTOOPT, "unsupported tcp-level option"),
@@ -40,5 +45,7 @@ pub fn new_tcp_option(name: i32) -> Result<Box<dyn RawSocketOption>> {
 impl_raw_socket_option!(NoDelay);
 impl_raw_socket_option!(MaxSegment);
 impl_raw_socket_option!(KeepIdle);
+impl_raw_socket_option!(KeepInterval);
+impl_raw_socket_option!(KeepCount);
 impl_raw_socket_option!(WindowClamp);
 impl_raw_socket_option!(Congestion);
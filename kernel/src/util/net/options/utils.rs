@@ -4,8 +4,15 @@ use core::time::Duration;
 
 use crate::{
     current_userspace,
-    net::socket::{ip::stream::CongestionControl, LingerOption},
+    net::socket::{
+        ip::{
+            options::{IpMreqn, IpMulticastIface},
+            stream::CongestionControl,
+        },
+        LingerOption, SocketFilter, UCred,
+    },
     prelude::*,
+    util::bpf::CSockFprog,
 };
 
 /// Create an object by reading its C counterpart from the user space.
@@ -67,6 +74,7 @@ macro_rules! impl_read_write_for_pod_type {
 }
 
 impl_read_write_for_pod_type!(u32);
+impl_read_write_for_pod_type!([u32; 8]);
 
 impl ReadFromUser for bool {
     fn read_from_user(addr: Vaddr, max_len: u32) -> Result<Self> {
@@ -138,6 +146,20 @@ impl WriteToUser for LingerOption {
     }
 }
 
+impl WriteToUser for UCred {
+    fn write_to_user(&self, addr: Vaddr, max_len: u32) -> Result<usize> {
+        let write_len = core::mem::size_of::<CUserCred>();
+
+        if (max_len as usize) < write_len {
+            return_errno_with_message!(Errno::EINVAL, "max_len is too short");
+        }
+
+        let ucred = CUserCred::from(*self);
+        current_userspace!().write_val(addr, &ucred)?;
+        Ok(write_len)
+    }
+}
+
 impl ReadFromUser for CongestionControl {
     fn read_from_user(addr: Vaddr, max_len: u32) -> Result<Self> {
         let mut bytes = vec![0; max_len as usize];
@@ -162,6 +184,93 @@ impl WriteToUser for CongestionControl {
     }
 }
 
+impl ReadFromUser for SocketFilter {
+    fn read_from_user(addr: Vaddr, max_len: u32) -> Result<Self> {
+        if (max_len as usize) < core::mem::size_of::<CSockFprog>() {
+            return_errno_with_message!(Errno::EINVAL, "max_len is too short");
+        }
+
+        let fprog = current_userspace!().read_val::<CSockFprog>(addr)?;
+        let insns = fprog.read_insns()?;
+
+        SocketFilter::new(insns)
+    }
+}
+
+impl ReadFromUser for IpMreqn {
+    fn read_from_user(addr: Vaddr, max_len: u32) -> Result<Self> {
+        // Like Linux, both `struct ip_mreqn` and `struct ip_mreq` are accepted.
+        if (max_len as usize) >= core::mem::size_of::<CIpMreqn>() {
+            let c_mreqn = current_userspace!().read_val::<CIpMreqn>(addr)?;
+            return Ok(IpMreqn {
+                multiaddr: c_mreqn.imr_multiaddr.into(),
+                address: c_mreqn.imr_address.into(),
+                ifindex: c_mreqn.imr_ifindex as u32,
+            });
+        }
+
+        if (max_len as usize) >= core::mem::size_of::<CIpMreq>() {
+            let c_mreq = current_userspace!().read_val::<CIpMreq>(addr)?;
+            return Ok(IpMreqn {
+                multiaddr: c_mreq.imr_multiaddr.into(),
+                address: c_mreq.imr_interface.into(),
+                ifindex: 0,
+            });
+        }
+
+        return_errno_with_message!(Errno::EINVAL, "max_len is too short");
+    }
+}
+
+impl ReadFromUser for IpMulticastIface {
+    fn read_from_user(addr: Vaddr, max_len: u32) -> Result<Self> {
+        // Like Linux, `struct ip_mreqn`, `struct ip_mreq`, and `struct in_addr` are accepted.
+        if (max_len as usize) >= core::mem::size_of::<CIpMreq>() {
+            let request = IpMreqn::read_from_user(addr, max_len)?;
+            return Ok(IpMulticastIface {
+                addr: request.address,
+                ifindex: request.ifindex,
+            });
+        }
+
+        if (max_len as usize) >= core::mem::size_of::<[u8; 4]>() {
+            let addr = current_userspace!().read_val::<[u8; 4]>(addr)?;
+            return Ok(IpMulticastIface {
+                addr: addr.into(),
+                ifindex: 0,
+            });
+        }
+
+        return_errno_with_message!(Errno::EINVAL, "max_len is too short");
+    }
+}
+
+impl WriteToUser for IpMulticastIface {
+    fn write_to_user(&self, addr: Vaddr, max_len: u32) -> Result<usize> {
+        // Like Linux, only the address is written as a `struct in_addr`.
+        let write_len = core::mem::size_of::<[u8; 4]>();
+
+        if (max_len as usize) < write_len {
+            return_errno_with_message!(Errno::EINVAL, "max_len is too short");
+        }
+
+        current_userspace!().write_val(addr, &self.addr.octets())?;
+        Ok(write_len)
+    }
+}
+
+impl ReadFromUser for () {
+    fn read_from_user(_addr: Vaddr, max_len: u32) -> Result<Self> {
+        // This is for options that do not have values (e.g., `SO_DETACH_FILTER`). Like Linux, the
+        // length must still be at least the size of an integer, but the value is ignored.
+        if (max_len as usize) < core::mem::size_of::<i32>() {
+            return_errno_with_message!(Errno::EINVAL, "max_len is too short");
+        }
+
+        Ok(())
+    }
+}
+
 #[repr(C)]
 #[derive(Debug, Clone, Copy, Pod)]
 struct CLinger {
@@ -186,3 +295,38 @@ impl From<CLinger> for LingerOption {
         LingerOption::new(is_on, timeout)
     }
 }
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Pod)]
+struct CUserCred {
+    pid: i32,
+    uid: u32,
+    gid: u32,
+}
+
+impl From<UCred> for CUserCred {
+    fn from(value: UCred) -> Self {
+        Self {
+            pid: value.pid as i32,
+            uid: value.uid.into(),
+            gid: value.gid.into(),
+        }
+    }
+}
+
+/// A request to join or leave an IPv4 multicast group (`struct ip_mreqn`).
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Pod)]
+struct CIpMreqn {
+    imr_multiaddr: [u8; 4],
+    imr_address: [u8; 4],
+    imr_ifindex: i32,
+}
+
+/// The legacy version of [`CIpMreqn`] (`struct ip_mreq`).
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Pod)]
+struct CIpMreq {
+    imr_multiaddr: [u8; 4],
+    imr_interface: [u8; 4],
+}
@@ -1,9 +1,14 @@
 // SPDX-License-Identifier: MPL-2.0
 
-use super::read_socket_addr_from_user;
+use super::{read_socket_addr_from_user, CSocketOptionLevel};
 use crate::{
-    net::socket::SocketAddr,
+    fs::{
+        file_handle::FileLike,
+        file_table::{max_fds, FdFlags},
+    },
+    net::socket::{ControlMessage, SendRecvFlags, SocketAddr, UCred},
     prelude::*,
+    process::{credentials::capabilities::CapSet, Gid, Uid},
     util::{net::write_socket_addr_with_max_len, VmReaderArray, VmWriterArray},
 };
 
@@ -28,6 +33,7 @@ pub enum Protocol {
     IPPROTO_GRE = 47,       /* Cisco GRE tunnels (rfc 1701,1702)	*/
     IPPROTO_ESP = 50,       /* Encapsulation Security Payload protocol */
     IPPROTO_AH = 51,        /* Authentication Header protocol	*/
+    IPPROTO_ICMPV6 = 58,    /* ICMPv6				*/
     IPPROTO_MTP = 92,       /* Multicast Transport Protocol		*/
     IPPROTO_BEETPH = 94,    /* IP option pseudo header for BEET	*/
     IPPROTO_ENCAP = 98,     /* Encapsulation Header			*/
@@ -84,11 +90,11 @@ pub struct CUserMsgHdr {
     /// Scatter/Gather iov array
     pub msg_iov: Vaddr,
     /// The # of elements in msg_iov
-    pub msg_iovlen: u32,
+    pub msg_iovlen: usize,
     /// Ancillary data
     pub msg_control: Vaddr,
     /// Ancillary data buffer length
-    pub msg_controllen: u32,
+    pub msg_controllen: usize,
     /// Flags on received message
     pub msg_flags: u32,
 }
@@ -112,11 +118,283 @@ impl CUserMsgHdr {
         Ok(())
     }
 
-    pub fn copy_reader_array_from_user<'a>(&self, ctx: &'a Context) -> Result<VmReaderArray<'a>> {
-        VmReaderArray::from_user_io_vecs(ctx, self.msg_iov, self.msg_iovlen as usize)
+    pub fn copy_reader_array_from_user<'a>(
+        &self,
+        user_space: &'a CurrentUserSpace,
+    ) -> Result<VmReaderArray<'a>> {
+        VmReaderArray::from_user_io_vecs(user_space, self.msg_iov, self.msg_iovlen as usize)
     }
 
-    pub fn copy_writer_array_from_user<'a>(&self, ctx: &'a Context) -> Result<VmWriterArray<'a>> {
-        VmWriterArray::from_user_io_vecs(ctx, self.msg_iov, self.msg_iovlen as usize)
+    pub fn copy_writer_array_from_user<'a>(
+        &self,
+        user_space: &'a CurrentUserSpace,
+    ) -> Result<VmWriterArray<'a>> {
+        VmWriterArray::from_user_io_vecs(user_space, self.msg_iov, self.msg_iovlen as usize)
     }
+
+    /// Reads the control messages from the user space.
+    ///
+    /// The file descriptors in `SCM_RIGHTS` messages are resolved to files, and the credentials
+    /// in `SCM_CREDENTIALS` messages are checked against those of the current process.
+    pub fn read_control_messages_from_user(&self, ctx: &Context) -> Result<Vec<ControlMessage>> {
+        let mut control_messages = Vec::new();
+
+        let user_space = ctx.user_space();
+        let mut offset = 0;
+        while offset + CONTROL_HEADER_LEN <= self.msg_controllen {
+            let header: CControlHeader = user_space.read_val(self.msg_control + offset)?;
+            if header.cmsg_len < CONTROL_HEADER_LEN
+                || header.cmsg_len > self.msg_controllen - offset
+            {
+                return_errno_with_message!(Errno::EINVAL, "the control message length is invalid");
+            }
+
+            let data_addr = self.msg_control + offset + CONTROL_HEADER_LEN;
+            let data_len = header.cmsg_len - CONTROL_HEADER_LEN;
+            offset += align_control_len(header.cmsg_len);
+
+            // Similar to Linux, the control messages of other levels are silently ignored.
+            if !matches!(
+                CSocketOptionLevel::try_from(header.cmsg_level),
+                Ok(CSocketOptionLevel::SOL_SOCKET)
+            ) {
+                warn!("unsupported control message level: {}", header.cmsg_level);
+                continue;
+            }
+
+            let control_message = match CControlType::try_from(header.cmsg_type) {
+                Ok(CControlType::SCM_RIGHTS) => read_files_from_user(data_addr, data_len, ctx)?,
+                Ok(CControlType::SCM_CREDENTIALS) => {
+                    read_credentials_from_user(data_addr, data_len, ctx)?
+                }
+                Err(_) => {
+                    return_errno_with_message!(Errno::EINVAL, "unsupported control message type")
+                }
+            };
+            control_messages.push(control_message);
+        }
+
+        Ok(control_messages)
+    }
+
+    /// Writes the control messages to the user space.
+    ///
+    /// The files in `SCM_RIGHTS` messages are installed into the file table. If `is_cloexec` is
+    /// true, the new file descriptors are marked as close-on-exec.
+    ///
+    /// This method updates `msg_controllen` to the length of the written control messages, and
+    /// sets `MSG_CTRUNC` in `msg_flags` if some control data is discarded due to insufficient
+    /// buffer space.
+    pub fn write_control_messages_to_user(
+        &mut self,
+        control_messages: &[ControlMessage],
+        is_cloexec: bool,
+        ctx: &Context,
+    ) -> Result<()> {
+        let user_space = ctx.user_space();
+        let mut offset = 0;
+        let mut is_truncated = false;
+
+        for control_message in control_messages {
+            let avail_len = self
+                .msg_controllen
+                .saturating_sub(offset)
+                .saturating_sub(CONTROL_HEADER_LEN);
+
+            let (cmsg_level, cmsg_type, data) = match control_message {
+                ControlMessage::Files(files) => {
+                    let max_files = avail_len / core::mem::size_of::<i32>();
+                    if max_files < files.len() {
+                        is_truncated = true;
+                    }
+                    if max_files == 0 {
+                        continue;
+                    }
+                    let num_files = max_files.min(files.len());
+                    let data = install_files(&files[..num_files], is_cloexec, ctx);
+                    if data.len() < num_files * core::mem::size_of::<i32>() {
+                        is_truncated = true;
+                    }
+                    (
+                        CSocketOptionLevel::SOL_SOCKET,
+                        CControlType::SCM_RIGHTS as i32,
+                        data,
+                    )
+                }
+                ControlMessage::Credentials(cred) => {
+                    if avail_len < core::mem::size_of::<CControlCred>() {
+                        is_truncated = true;
+                        continue;
+                    }
+                    let data = CControlCred::from(*cred).as_bytes().to_vec();
+                    (
+                        CSocketOptionLevel::SOL_SOCKET,
+                        CControlType::SCM_CREDENTIALS as i32,
+                        data,
+                    )
+                }
+                ControlMessage::Ttl(hop_limit) | ControlMessage::HopLimit(hop_limit) => {
+                    if avail_len < core::mem::size_of::<i32>() {
+                        is_truncated = true;
+                        continue;
+                    }
+                    let data = (*hop_limit as i32).as_bytes().to_vec();
+                    if matches!(control_message, ControlMessage::Ttl(_)) {
+                        (CSocketOptionLevel::SOL_IP, IP_TTL, data)
+                    } else {
+                        (CSocketOptionLevel::SOL_IPV6, IPV6_HOPLIMIT, data)
+                    }
+                }
+            };
+
+            let header = CControlHeader {
+                cmsg_len: CONTROL_HEADER_LEN + data.len(),
+                cmsg_level: cmsg_level as i32,
+                cmsg_type,
+            };
+            user_space.write_val(self.msg_control + offset, &header)?;
+            user_space.write_bytes(
+                self.msg_control + offset + CONTROL_HEADER_LEN,
+                &mut VmReader::from(data.as_slice()),
+            )?;
+            offset = (offset + align_control_len(header.cmsg_len)).min(self.msg_controllen);
+        }
+
+        self.msg_controllen = offset;
+        if is_truncated {
+            self.msg_flags |= SendRecvFlags::MSG_CTRUNC.bits() as u32;
+        }
+
+        Ok(())
+    }
+}
+
+/// The header of a control message (`struct cmsghdr`).
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Pod)]
+struct CControlHeader {
+    /// Data byte count, including the header
+    cmsg_len: usize,
+    /// Originating protocol
+    cmsg_level: i32,
+    /// Protocol-specific type
+    cmsg_type: i32,
+}
+
+const CONTROL_HEADER_LEN: usize = core::mem::size_of::<CControlHeader>();
+
+/// Aligns the length of a control message like `CMSG_ALIGN`.
+const fn align_control_len(len: usize) -> usize {
+    len.next_multiple_of(core::mem::size_of::<usize>())
+}
+
+/// The types of the `SOL_SOCKET` level control messages.
+#[repr(i32)]
+#[derive(Debug, Clone, Copy, TryFromInt)]
+#[allow(non_camel_case_types)]
+enum CControlType {
+    SCM_RIGHTS = 1,
+    SCM_CREDENTIALS = 2,
+}
+
+/// The type of the `SOL_IP` level control messages that carry the TTL.
+const IP_TTL: i32 = 2;
+/// The type of the `SOL_IPV6` level control messages that carry the hop limit.
+const IPV6_HOPLIMIT: i32 = 52;
+
+/// The credentials in `SCM_CREDENTIALS` messages (`struct ucred`).
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Pod)]
+struct CControlCred {
+    pid: i32,
+    uid: u32,
+    gid: u32,
+}
+
+impl From<UCred> for CControlCred {
+    fn from(value: UCred) -> Self {
+        Self {
+            pid: value.pid as i32,
+            uid: value.uid.into(),
+            gid: value.gid.into(),
+        }
+    }
+}
+
+/// The maximum number of file descriptors in an `SCM_RIGHTS` message.
+const SCM_MAX_FD: usize = 253;
+
+fn read_files_from_user(addr: Vaddr, len: usize, ctx: &Context) -> Result<ControlMessage> {
+    let num_fds = len / core::mem::size_of::<i32>();
+    if num_fds == 0 || num_fds > SCM_MAX_FD || len % core::mem::size_of::<i32>() != 0 {
+        return_errno_with_message!(Errno::EINVAL, "the number of file descriptors is invalid");
+    }
+
+    let mut bytes = vec![0u8; len];
+    ctx.user_space()
+        .read_bytes(addr, &mut VmWriter::from(bytes.as_mut_slice()))?;
+
+    let file_table = ctx.thread_local.file_table().borrow();
+    let file_table_locked = file_table.read();
+    let files = bytes
+        .chunks_exact(core::mem::size_of::<i32>())
+        .map(|fd| {
+            let fd = i32::from_ne_bytes(fd.try_into().unwrap());
+            file_table_locked.get_file(fd).cloned()
+        })
+        .collect::<Result<Vec<_>>>()?;
+
+    Ok(ControlMessage::Files(files))
+}
+
+fn read_credentials_from_user(addr: Vaddr, len: usize, ctx: &Context) -> Result<ControlMessage> {
+    if len != core::mem::size_of::<CControlCred>() {
+        return_errno_with_message!(Errno::EINVAL, "the credentials length is invalid");
+    }
+    let c_cred: CControlCred = ctx.user_space().read_val(addr)?;
+
+    let cred = UCred {
+        pid: c_cred.pid as _,
+        uid: Uid::new(c_cred.uid),
+        gid: Gid::new(c_cred.gid),
+    };
+
+    // Like Linux, a process can only send its own credentials, unless it has `CAP_SYS_ADMIN`
+    // for the PID, `CAP_SETUID` for the user ID, or `CAP_SETGID` for the group ID.
+    let credentials = ctx.posix_thread.credentials();
+    let capset = credentials.effective_capset();
+    let is_valid = (cred.pid == ctx.process.pid() || capset.contains(CapSet::SYS_ADMIN))
+        && ([credentials.ruid(), credentials.euid(), credentials.suid()].contains(&cred.uid)
+            || capset.contains(CapSet::SETUID))
+        && ([credentials.rgid(), credentials.egid(), credentials.sgid()].contains(&cred.gid)
+            || capset.contains(CapSet::SETGID));
+    if !is_valid {
+        return_errno_with_message!(Errno::EPERM, "the credentials cannot be sent");
+    }
+
+    Ok(ControlMessage::Credentials(cred))
+}
+
+/// Installs the files into the file table and returns the bytes of the new file descriptors.
+///
+/// If `RLIMIT_NOFILE` is reached, the remaining files are not installed and are discarded.
+fn install_files(files: &[Arc<dyn FileLike>], is_cloexec: bool, ctx: &Context) -> Vec<u8> {
+    let fd_flags = if is_cloexec {
+        FdFlags::CLOEXEC
+    } else {
+        FdFlags::empty()
+    };
+
+    let max_fds = max_fds(&ctx.process);
+    let file_table = ctx.thread_local.file_table().borrow();
+    let mut file_table_locked = file_table.write();
+    files
+        .iter()
+        .map_while(|file| {
+            file_table_locked
+                .insert(file.clone(), fd_flags, max_fds)
+                .ok()
+        })
+        .flat_map(|fd| fd.to_ne_bytes())
+        .collect()
 }
@@ -0,0 +1,148 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! The BLAKE2s-256 hash function, which mixes the entropy in the input pool.
+//!
+//! See RFC 7693 for the specification.
+
+/// The size of the hash in bytes.
+pub(super) const HASH_SIZE: usize = 32;
+
+const BLOCK_SIZE: usize = 64;
+
+const IV: [u32; 8] = [
+    0x6A09_E667,
+    0xBB67_AE85,
+    0x3C6E_F372,
+    0xA54F_F53A,
+    0x510E_527F,
+    0x9B05_688C,
+    0x1F83_D9AB,
+    0x5BE0_CD19,
+];
+
+const SIGMA: [[usize; 16]; 10] = [
+    [0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15],
+    [14, 10, 4, 8, 9, 15, 13, 6, 1, 12, 0, 2, 11, 7, 5, 3],
+    [11, 8, 12, 0, 5, 2, 15, 13, 10, 14, 3, 6, 7, 1, 9, 4],
+    [7, 9, 3, 1, 13, 12, 11, 14, 2, 6, 5, 10, 4, 0, 15, 8],
+    [9, 0, 5, 7, 2, 4, 10, 15, 14, 1, 11, 12, 6, 8, 3, 13],
+    [2, 12, 6, 10, 0, 11, 8, 3, 4, 13, 7, 5, 15, 14, 1, 9],
+    [12, 5, 1, 15, 14, 13, 4, 10, 0, 7, 6, 3, 9, 2, 8, 11],
+    [13, 11, 7, 14, 12, 1, 3, 9, 5, 0, 15, 4, 8, 6, 2, 10],
+    [6, 15, 14, 9, 11, 3, 0, 8, 12, 2, 13, 7, 1, 4, 10, 5],
+    [10, 2, 8, 4, 7, 6, 1, 5, 15, 11, 9, 14, 3, 12, 13, 0],
+];
+
+/// The state of an incremental BLAKE2s-256 hash.
+#[derive(Clone)]
+pub(super) struct Blake2s {
+    h: [u32; 8],
+    /// The number of bytes that have been compressed.
+    t: u64,
+    /// The last block, which is not compressed until more bytes arrive or the hash is finalized.
+    buf: [u8; BLOCK_SIZE],
+    buf_len: usize,
+}
+
+impl Blake2s {
+    /// Creates a hash state without a key.
+    pub(super) const fn new() -> Self {
+        let mut h = IV;
+        h[0] ^= 0x0101_0000 ^ HASH_SIZE as u32;
+        Self {
+            h,
+            t: 0,
+            buf: [0; BLOCK_SIZE],
+            buf_len: 0,
+        }
+    }
+
+    /// Creates a hash state keyed with `key`, which is at most [`HASH_SIZE`] bytes.
+    pub(super) fn new_keyed(key: &[u8]) -> Self {
+        debug_assert!(key.len() <= HASH_SIZE);
+
+        let mut state = Self::new();
+        if !key.is_empty() {
+            state.h[0] ^= (key.len() as u32) << 8;
+            // The key is padded to a full block, which is compressed before the data.
+            state.buf[..key.len()].copy_from_slice(key);
+            state.buf_len = BLOCK_SIZE;
+        }
+        state
+    }
+
+    pub(super) fn update(&mut self, mut data: &[u8]) {
+        while !data.is_empty() {
+            if self.buf_len == BLOCK_SIZE {
+                self.t += BLOCK_SIZE as u64;
+                self.compress(false);
+                self.buf_len = 0;
+            }
+            let len = data.len().min(BLOCK_SIZE - self.buf_len);
+            self.buf[self.buf_len..self.buf_len + len].copy_from_slice(&data[..len]);
+            self.buf_len += len;
+            data = &data[len..];
+        }
+    }
+
+    pub(super) fn finalize(mut self) -> [u8; HASH_SIZE] {
+        self.t += self.buf_len as u64;
+        self.buf[self.buf_len..].fill(0);
+        self.compress(true);
+
+        let mut output = [0; HASH_SIZE];
+        for (chunk, word) in output.chunks_exact_mut(4).zip(self.h) {
+            chunk.copy_from_slice(&word.to_le_bytes());
+        }
+        output
+    }
+
+    fn compress(&mut self, is_last: bool) {
+        let mut m = [0u32; 16];
+        for (word, chunk) in m.iter_mut().zip(self.buf.chunks_exact(4)) {
+            *word = u32::from_le_bytes(chunk.try_into().unwrap());
+        }
+
+        let mut v = [0u32; 16];
+        v[..8].copy_from_slice(&self.h);
+        v[8..].copy_from_slice(&IV);
+        v[12] ^= self.t as u32;
+        v[13] ^= (self.t >> 32) as u32;
+        if is_last {
+            v[14] = !v[14];
+        }
+
+        for s in SIGMA.iter() {
+            g(&mut v, 0, 4, 8, 12, m[s[0]], m[s[1]]);
+            g(&mut v, 1, 5, 9, 13, m[s[2]], m[s[3]]);
+            g(&mut v, 2, 6, 10, 14, m[s[4]], m[s[5]]);
+            g(&mut v, 3, 7, 11, 15, m[s[6]], m[s[7]]);
+            g(&mut v, 0, 5, 10, 15, m[s[8]], m[s[9]]);
+            g(&mut v, 1, 6, 11, 12, m[s[10]], m[s[11]]);
+            g(&mut v, 2, 7, 8, 13, m[s[12]], m[s[13]]);
+            g(&mut v, 3, 4, 9, 14, m[s[14]], m[s[15]]);
+        }
+
+        for (i, h) in self.h.iter_mut().enumerate() {
+            *h ^= v[i] ^ v[i + 8];
+        }
+    }
+}
+
+fn g(v: &mut [u32; 16], a: usize, b: usize, c: usize, d: usize, x: u32, y: u32) {
+    v[a] = v[a].wrapping_add(v[b]).wrapping_add(x);
+    v[d] = (v[d] ^ v[a]).rotate_right(16);
+    v[c] = v[c].wrapping_add(v[d]);
+    v[b] = (v[b] ^ v[c]).rotate_right(12);
+    v[a] = v[a].wrapping_add(v[b]).wrapping_add(y);
+    v[d] = (v[d] ^ v[a]).rotate_right(8);
+    v[c] = v[c].wrapping_add(v[d]);
+    v[b] = (v[b] ^ v[c]).rotate_right(7);
+}
+
+/// Computes the keyed hash of `data` in one shot.
+pub(super) fn blake2s_keyed(key: &[u8], data: &[u8]) -> [u8; HASH_SIZE] {
+    let mut state = Blake2s::new_keyed(key);
+    state.update(data);
+    state.finalize()
+}
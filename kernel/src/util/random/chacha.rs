@@ -0,0 +1,57 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! The ChaCha20 block function, which generates the output of the CRNG.
+//!
+//! See RFC 8439 for the specification. Like Linux, the original layout with a 64-bit block
+//! counter and a 64-bit nonce is used.
+
+/// The size of the key in bytes.
+pub(super) const KEY_SIZE: usize = 32;
+
+/// The size of a block in bytes.
+pub(super) const BLOCK_SIZE: usize = 64;
+
+/// The constant words ("expand 32-byte k").
+const CONSTANTS: [u32; 4] = [0x6170_7865, 0x3320_646E, 0x7962_2D32, 0x6B20_6574];
+
+/// Computes the ChaCha20 block of the key at the counter and the nonce.
+pub(super) fn chacha20_block(key: &[u8; KEY_SIZE], counter: u64, nonce: u64) -> [u8; BLOCK_SIZE] {
+    let mut state = [0u32; 16];
+    state[..4].copy_from_slice(&CONSTANTS);
+    for (word, chunk) in state[4..12].iter_mut().zip(key.chunks_exact(4)) {
+        *word = u32::from_le_bytes(chunk.try_into().unwrap());
+    }
+    state[12] = counter as u32;
+    state[13] = (counter >> 32) as u32;
+    state[14] = nonce as u32;
+    state[15] = (nonce >> 32) as u32;
+
+    let mut x = state;
+    for _ in 0..10 {
+        quarter_round(&mut x, 0, 4, 8, 12);
+        quarter_round(&mut x, 1, 5, 9, 13);
+        quarter_round(&mut x, 2, 6, 10, 14);
+        quarter_round(&mut x, 3, 7, 11, 15);
+        quarter_round(&mut x, 0, 5, 10, 15);
+        quarter_round(&mut x, 1, 6, 11, 12);
+        quarter_round(&mut x, 2, 7, 8, 13);
+        quarter_round(&mut x, 3, 4, 9, 14);
+    }
+
+    let mut output = [0; BLOCK_SIZE];
+    for ((chunk, x), state) in output.chunks_exact_mut(4).zip(x).zip(state) {
+        chunk.copy_from_slice(&x.wrapping_add(state).to_le_bytes());
+    }
+    output
+}
+
+fn quarter_round(x: &mut [u32; 16], a: usize, b: usize, c: usize, d: usize) {
+    x[a] = x[a].wrapping_add(x[b]);
+    x[d] = (x[d] ^ x[a]).rotate_left(16);
+    x[c] = x[c].wrapping_add(x[d]);
+    x[b] = (x[b] ^ x[c]).rotate_left(12);
+    x[a] = x[a].wrapping_add(x[b]);
+    x[d] = (x[d] ^ x[a]).rotate_left(8);
+    x[c] = x[c].wrapping_add(x[d]);
+    x[b] = (x[b] ^ x[c]).rotate_left(7);
+}
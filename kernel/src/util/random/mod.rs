@@ -0,0 +1,238 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! The kernel random number generator.
+//!
+//! Like Linux, the generator consists of two parts:
+//!  - The input pool collects the entropy from various sources and mixes it with BLAKE2s. The
+//!    pool also accounts for the entropy credited by the sources, up to [`POOL_BITS`] bits.
+//!  - The CRNG (cryptographic random number generator) generates the random bytes with ChaCha20.
+//!    Its key is seeded from the input pool and is replaced after each use (i.e., fast key
+//!    erasure), so the past outputs cannot be recovered from the current state.
+//!
+//! The CRNG is _ready_ once the input pool has been credited with [`POOL_BITS`] bits of
+//! entropy. Before that, its output may be predictable. `/dev/random` and `getrandom` without
+//! `GRND_INSECURE` wait until the CRNG is ready, while `/dev/urandom` and the kernel users never
+//! wait.
+//!
+//! The input pool is filled at boot with the entropy from the CPU (RDRAND on x86-64), the
+//! bootloader (`rng-seed` on RISC-V), the virtio-rng devices and the timing jitter. The CRNG is
+//! reseeded with fresh entropy from these sources periodically, at most every 60 seconds.
+
+use core::{
+    sync::atomic::{AtomicBool, Ordering},
+    time::Duration,
+};
+
+use ostd::{sync::WaitQueue, timer::Jiffies};
+
+use self::{
+    blake2s::{blake2s_keyed, Blake2s, HASH_SIZE},
+    chacha::{chacha20_block, BLOCK_SIZE, KEY_SIZE},
+};
+use crate::prelude::*;
+
+mod blake2s;
+mod chacha;
+mod source;
+
+/// The capacity of the input pool in bits, which is also the entropy to make the CRNG ready.
+pub const POOL_BITS: usize = HASH_SIZE * 8;
+
+/// The minimal interval between two reseeds of the CRNG.
+const MIN_RESEED_INTERVAL: Duration = Duration::from_secs(1);
+/// The maximal interval between two reseeds of the CRNG.
+const MAX_RESEED_INTERVAL: Duration = Duration::from_secs(60);
+
+static INPUT_POOL: SpinLock<InputPool> = SpinLock::new(InputPool::new());
+static CRNG: SpinLock<Crng> = SpinLock::new(Crng::new());
+static IS_READY: AtomicBool = AtomicBool::new(false);
+static READY_WAIT_QUEUE: WaitQueue = WaitQueue::new();
+
+/// Fills `dst` with random bytes.
+///
+/// This function never waits for the CRNG to be ready. Use [`is_ready`] or [`wait_until_ready`]
+/// if the bytes must be unpredictable.
+pub fn getrandom(dst: &mut [u8]) {
+    let key = next_key();
+    for (counter, chunk) in dst.chunks_mut(BLOCK_SIZE).enumerate() {
+        let block = chacha20_block(&key, counter as u64, 0);
+        chunk.copy_from_slice(&block[..chunk.len()]);
+    }
+}
+
+/// Fills `writer` with random bytes, returning the number of bytes written.
+///
+/// Like [`getrandom`], this function never waits for the CRNG to be ready.
+pub fn getrandom_user(writer: &mut VmWriter) -> Result<usize> {
+    let mut buf = vec![0; writer.avail().min(PAGE_SIZE)];
+    let mut written = 0;
+
+    while writer.has_avail() {
+        let len = writer.avail().min(PAGE_SIZE);
+        getrandom(&mut buf[..len]);
+        match writer.write_fallible(&mut buf[..len].into()) {
+            Ok(len) => written += len,
+            // Like Linux, the bytes written before a fault are returned.
+            Err((_, len)) if written + len > 0 => return Ok(written + len),
+            Err((err, _)) => return Err(err.into()),
+        }
+    }
+
+    Ok(written)
+}
+
+/// Returns whether the CRNG has been seeded with enough entropy.
+pub fn is_ready() -> bool {
+    IS_READY.load(Ordering::Acquire)
+}
+
+/// Waits until the CRNG is ready.
+///
+/// Like Linux, this function tries to collect the entropy from the timing jitter before
+/// sleeping. The waiting can be interrupted by signals.
+pub fn wait_until_ready() -> Result<()> {
+    if is_ready() {
+        return Ok(());
+    }
+
+    source::add_jitter_entropy(POOL_BITS);
+    READY_WAIT_QUEUE.pause_until(|| is_ready().then_some(()))
+}
+
+/// Mixes `data` into the input pool and credits `bits` bits of entropy.
+pub fn add_entropy(data: &[u8], bits: usize) {
+    let becomes_ready = {
+        let mut pool = INPUT_POOL.lock();
+        pool.hash.update(data);
+
+        let was_full = pool.entropy_bits >= POOL_BITS;
+        pool.entropy_bits = (pool.entropy_bits + bits).min(POOL_BITS);
+        !was_full && pool.entropy_bits >= POOL_BITS
+    };
+
+    if becomes_ready {
+        reseed_crng();
+        if !IS_READY.swap(true, Ordering::AcqRel) {
+            info!("random: crng init done");
+            READY_WAIT_QUEUE.wake_all();
+        }
+    }
+}
+
+/// Mixes `data` into the input pool without crediting any entropy.
+///
+/// This is for the data that differs between machines or boots but may be known to an
+/// attacker, e.g., the timestamps and the hardware addresses.
+pub fn add_device_randomness(data: &[u8]) {
+    add_entropy(data, 0);
+}
+
+/// Returns the entropy credited to the input pool in bits.
+///
+/// Like Linux, the credited entropy is not consumed by reseeding the CRNG, so it is always
+/// [`POOL_BITS`] once the CRNG is ready.
+pub fn entropy_avail() -> usize {
+    INPUT_POOL.lock().entropy_bits
+}
+
+/// Reseeds the CRNG with fresh entropy from the hardware sources.
+pub fn reseed() {
+    // The hardware sources are read without holding any locks, since reading the virtio-rng
+    // devices busy waits.
+    source::add_hardware_entropy();
+    reseed_crng();
+}
+
+pub fn init() {
+    source::add_boot_entropy();
+    if !is_ready() {
+        warn!("random: the hardware entropy is insufficient, using the timing jitter");
+        source::add_jitter_entropy(POOL_BITS);
+    }
+    // The CRNG must be seeded even if it is not ready.
+    reseed_crng();
+}
+
+/// The input pool, which collects the entropy.
+struct InputPool {
+    hash: Blake2s,
+    entropy_bits: usize,
+}
+
+impl InputPool {
+    const fn new() -> Self {
+        Self {
+            hash: Blake2s::new(),
+            entropy_bits: 0,
+        }
+    }
+
+    /// Extracts a seed from the pool.
+    ///
+    /// Like Linux, the pool is rekeyed with a key derived from the seed, so the seed cannot be
+    /// recovered from the new state of the pool.
+    fn extract(&mut self) -> [u8; HASH_SIZE] {
+        let seed = core::mem::replace(&mut self.hash, Blake2s::new()).finalize();
+        self.hash = Blake2s::new_keyed(&blake2s_keyed(&seed, &[0]));
+        blake2s_keyed(&seed, &[1])
+    }
+}
+
+/// The state of the CRNG.
+struct Crng {
+    key: [u8; KEY_SIZE],
+    /// The time of the last reseed since boot.
+    last_reseed: Duration,
+}
+
+impl Crng {
+    const fn new() -> Self {
+        Self {
+            key: [0; KEY_SIZE],
+            last_reseed: Duration::ZERO,
+        }
+    }
+
+    /// Returns whether the CRNG should be reseeded.
+    ///
+    /// Like Linux, the interval between reseeds starts from [`MIN_RESEED_INTERVAL`] and grows
+    /// with the uptime to [`MAX_RESEED_INTERVAL`], so the CRNG recovers quickly at boot if the
+    /// initial seed is weak.
+    fn needs_reseed(&self, now: Duration) -> bool {
+        let interval = (now / 2).clamp(MIN_RESEED_INTERVAL, MAX_RESEED_INTERVAL);
+        now >= self.last_reseed + interval
+    }
+}
+
+fn reseed_crng() {
+    let seed = INPUT_POOL.lock().extract();
+
+    let mut crng = CRNG.lock();
+    crng.key = seed;
+    crng.last_reseed = Jiffies::elapsed().as_duration();
+}
+
+/// Returns the key for the next output, reseeding the CRNG if needed.
+fn next_key() -> [u8; KEY_SIZE] {
+    let now = Jiffies::elapsed().as_duration();
+    let needs_reseed = {
+        let mut crng = CRNG.lock();
+        let needs_reseed = crng.needs_reseed(now);
+        if needs_reseed {
+            // Update the time now so that other threads do not reseed again.
+            crng.last_reseed = now;
+        }
+        needs_reseed
+    };
+    if needs_reseed {
+        reseed();
+    }
+
+    // The first half of the block replaces the key of the CRNG (i.e., fast key erasure), and
+    // the second half is the key for the output. The output can then be generated without
+    // holding the lock.
+    let mut crng = CRNG.lock();
+    let block = chacha20_block(&crng.key, 0, 0);
+    crng.key.copy_from_slice(&block[..KEY_SIZE]);
+    block[KEY_SIZE..].try_into().unwrap()
+}
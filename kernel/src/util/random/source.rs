@@ -0,0 +1,115 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! The entropy sources of the input pool.
+
+use ostd::arch::read_tsc;
+
+use super::{add_device_randomness, add_entropy, blake2s::Blake2s, POOL_BITS};
+
+/// Adds the entropy that is available at boot.
+pub(super) fn add_boot_entropy() {
+    add_device_randomness(&read_tsc().to_ne_bytes());
+
+    cfg_if::cfg_if! {
+        if #[cfg(target_arch = "x86_64")] {
+            add_cpu_entropy();
+        } else if #[cfg(any(target_arch = "riscv64", target_arch = "aarch64"))] {
+            use ostd::arch::boot::DEVICE_TREE;
+
+            // Like Linux with `random.trust_bootloader=on`, the seed from the bootloader is
+            // trusted.
+            let seed = DEVICE_TREE
+                .get()
+                .and_then(|device_tree| device_tree.find_node("/chosen"))
+                .and_then(|chosen| chosen.property("rng-seed"));
+            if let Some(seed) = seed {
+                add_entropy(seed.value, seed.value.len() * 8);
+            }
+        } else {
+            compile_error!("unsupported target");
+        }
+    }
+
+    add_device_entropy();
+}
+
+/// Adds the entropy from the hardware sources, which is used to reseed the CRNG periodically.
+pub(super) fn add_hardware_entropy() {
+    #[cfg(target_arch = "x86_64")]
+    add_cpu_entropy();
+    add_device_entropy();
+    add_jitter_entropy(JITTER_BATCH_BITS);
+}
+
+/// Adds the entropy from the random number generator of the CPU (i.e., RDRAND).
+///
+/// Like Linux with `random.trust_cpu=on`, the CPU is trusted.
+#[cfg(target_arch = "x86_64")]
+fn add_cpu_entropy() {
+    use log::warn;
+    use ostd::arch::read_random;
+
+    for _ in 0..POOL_BITS / u64::BITS as usize {
+        let Some(value) = read_random() else {
+            warn!("random: RDRAND failed multiple times");
+            return;
+        };
+        add_entropy(&value.to_ne_bytes(), u64::BITS as usize);
+    }
+}
+
+/// Adds the entropy from the virtio-rng devices.
+fn add_device_entropy() {
+    for device in aster_virtio::device::entropy::all_devices() {
+        let mut buf = [0; POOL_BITS / 8];
+        let len = device.read(&mut buf);
+        add_entropy(&buf[..len], len * 8);
+    }
+}
+
+/// The number of timing samples in a batch.
+const JITTER_BATCH_SIZE: usize = 64;
+/// The number of varying samples that are credited as one bit of entropy.
+const JITTER_SAMPLES_PER_BIT: usize = 8;
+/// The maximum number of bits that are credited for a batch.
+const JITTER_BATCH_BITS: usize = JITTER_BATCH_SIZE / JITTER_SAMPLES_PER_BIT;
+/// The maximum number of batches in one call, which bounds the time spent on a slow timer.
+const MAX_JITTER_BATCHES: usize = 4096;
+
+/// Adds the entropy from the timing jitter of the CPU, until `bits` bits are credited.
+///
+/// The execution time of a memory-intensive computation varies because of caches, pipelines and
+/// interrupts. Like the jitter entropy source of Linux, the timing is sampled repeatedly, and only
+/// the samples whose time differs from the previous one are credited, conservatively.
+pub(super) fn add_jitter_entropy(bits: usize) {
+    let mut credited = 0;
+    let mut last_delta = 0;
+
+    for _ in 0..MAX_JITTER_BATCHES {
+        if credited >= bits {
+            break;
+        }
+
+        let mut samples = [0u8; JITTER_BATCH_SIZE * size_of::<u64>()];
+        let mut num_varying = 0;
+        let mut hash = Blake2s::new();
+        for i in 0..JITTER_BATCH_SIZE {
+            let start = read_tsc();
+            hash.update(&samples);
+            let delta = read_tsc().wrapping_sub(start);
+
+            if delta != last_delta {
+                num_varying += 1;
+            }
+            last_delta = delta;
+            samples[i * size_of::<u64>()..(i + 1) * size_of::<u64>()]
+                .copy_from_slice(&delta.to_ne_bytes());
+        }
+        // The computation is only for the timing, so it must not be optimized out.
+        core::hint::black_box(hash);
+
+        let batch_bits = num_varying / JITTER_SAMPLES_PER_BIT;
+        add_entropy(&samples, batch_bits);
+        credited += batch_bits;
+    }
+}
@@ -11,14 +11,26 @@
 //! necessary time-related information, and a Virtual Memory Object (VMO) that encapsulates both the data and the
 //! VDSO routines. The VMO is intended to be mapped into the address space of every user space process for efficient access.
 //!
-//! The module is initialized with `init`, which sets up the `START_SECS_COUNT` and prepares the VDSO instance for
-//! use. It also hooks up the VDSO data update routine to the time management subsystem for periodic updates.
+//! The module is initialized with `init`, which prepares the VDSO instance for use. It also hooks up the VDSO data
+//! update routine to the time management subsystem for periodic updates.
+//!
+//! The VDSO calculates the high-resolution clocks from the TSC. Like Linux, the clocks slewed by NTP adjustments are
+//! calculated with a multiplier adjusted by the current rate, while `CLOCK_MONOTONIC_RAW` is calculated with a
+//! separate `VdsoData` at the raw rate. When a leap second is pending, the clock mode of the slewed clocks is set to
+//! `None` so that the VDSO routines fall back to the system calls.
+//!
+//! The `getcpu` routine of the VDSO gets the number of the current CPU from the limit of the CPUNODE segment in the
+//! GDT, which is set up by OSTD for each CPU.
 
 use alloc::{boxed::Box, sync::Arc};
-use core::{mem::ManuallyDrop, time::Duration};
+use core::{
+    mem::ManuallyDrop,
+    sync::atomic::{fence, Ordering},
+    time::Duration,
+};
 
 use aster_rights::Rights;
-use aster_time::{read_monotonic_time, Instant};
+use aster_time::Instant;
 use aster_util::coeff::Coeff;
 use ostd::{
     mm::{UFrame, VmIo, PAGE_SIZE},
@@ -30,15 +42,27 @@ use spin::Once;
 use crate::{
     fs::fs_resolver::{FsPath, FsResolver, AT_FDCWD},
     syscall::ClockId,
-    time::{clocks::MonotonicClock, timer::Timeout, SystemTime, START_TIME},
+    time::{
+        clocks::MonotonicClock,
+        timekeeping::{self, TimeSnapshot},
+        timer::Timeout,
+    },
     vm::vmo::{Vmo, VmoOptions},
 };
 
-const CLOCK_TAI: usize = 11;
-const VDSO_BASES: usize = CLOCK_TAI + 1;
+const VDSO_BASES: usize = ClockId::CLOCK_TAI as usize + 1;
 const DEFAULT_CLOCK_MODE: VdsoClockMode = VdsoClockMode::Tsc;
 
-static START_SECS_COUNT: Once<u64> = Once::new();
+/// The offset of the `VdsoData` instances in the VDSO VMO.
+const VDSO_DATA_OFFSET: usize = 0x80;
+/// The index of the `VdsoData` for the clocks slewed by NTP adjustments.
+const CS_HRES: usize = 0;
+/// The index of the `VdsoData` for `CLOCK_MONOTONIC_RAW`.
+const CS_RAW: usize = 1;
+
+/// The resolution of the high-resolution clocks in nanoseconds.
+const HRTIMER_RES_NSEC: u32 = 1;
+
 static VDSO: Once<Arc<Vdso>> = Once::new();
 
 #[derive(Debug, Copy, Clone)]
@@ -106,10 +130,12 @@ struct VdsoData {
 const HIGH_RES_CLOCK_IDS: [ClockId; 4] = [
     ClockId::CLOCK_REALTIME,
     ClockId::CLOCK_MONOTONIC,
-    ClockId::CLOCK_MONOTONIC_RAW,
     ClockId::CLOCK_BOOTTIME,
+    ClockId::CLOCK_TAI,
 ];
 
+const RAW_CLOCK_IDS: [ClockId; 1] = [ClockId::CLOCK_MONOTONIC_RAW];
+
 const COARSE_RES_CLOCK_IDS: [ClockId; 2] = [
     ClockId::CLOCK_REALTIME_COARSE,
     ClockId::CLOCK_MONOTONIC_COARSE,
@@ -135,14 +161,11 @@ impl VdsoData {
 
     /// Init VDSO data based on the default clocksource.
     fn init(&mut self) {
-        let clocksource = aster_time::default_clocksource();
-        let coeff = clocksource.coeff();
+        let coeff = aster_time::default_clocksource().coeff();
         self.set_clock_mode(DEFAULT_CLOCK_MODE);
         self.set_coeff(coeff);
-
-        let (last_instant, last_cycles) = clocksource.last_record();
-        self.update_high_res_instant(last_instant, last_cycles);
-        self.update_coarse_res_instant(last_instant);
+        self.mask = u64::MAX;
+        self.hrtimer_res = HRTIMER_RES_NSEC;
     }
 
     fn set_clock_mode(&mut self, mode: VdsoClockMode) {
@@ -159,35 +182,62 @@ impl VdsoData {
         self.basetime[clockid].nanos_info = nanos_info;
     }
 
-    fn update_high_res_instant(&mut self, instant: Instant, instant_cycles: u64) {
-        self.last_cycles = instant_cycles;
-        for clock_id in HIGH_RES_CLOCK_IDS {
-            let secs = if clock_id == ClockId::CLOCK_REALTIME {
-                instant.secs() + START_SECS_COUNT.get().unwrap()
-            } else {
-                instant.secs()
-            };
+    /// Updates the clocks with their times at `instant_cycles`, from which the clocks advance by
+    /// the cycles converted with `mult`.
+    ///
+    /// If `mult` is `None`, the VDSO routines cannot calculate the clocks and fall back to the
+    /// system calls.
+    fn update_high_res_instant(
+        &mut self,
+        clock_ids: &[ClockId],
+        snapshot: &TimeSnapshot,
+        instant_cycles: u64,
+        mult: Option<u32>,
+    ) {
+        match mult {
+            Some(mult) => {
+                self.set_clock_mode(DEFAULT_CLOCK_MODE);
+                self.mult = mult;
+            }
+            None => self.set_clock_mode(VdsoClockMode::None),
+        }
 
+        self.last_cycles = instant_cycles;
+        for &clock_id in clock_ids {
+            let time = clock_time(snapshot, clock_id);
             self.update_clock_instant(
                 clock_id as usize,
-                secs,
-                (instant.nanos() as u64) << self.shift as u64,
+                time.as_secs(),
+                (time.subsec_nanos() as u64) << self.shift as u64,
             );
         }
     }
 
-    fn update_coarse_res_instant(&mut self, instant: Instant) {
+    fn update_coarse_res_instant(&mut self, snapshot: TimeSnapshot) {
         for clock_id in COARSE_RES_CLOCK_IDS {
-            let secs = if clock_id == ClockId::CLOCK_REALTIME_COARSE {
-                instant.secs() + START_SECS_COUNT.get().unwrap()
-            } else {
-                instant.secs()
-            };
-            self.update_clock_instant(clock_id as usize, secs, instant.nanos() as u64);
+            let time = clock_time(&snapshot, clock_id);
+            self.update_clock_instant(
+                clock_id as usize,
+                time.as_secs(),
+                time.subsec_nanos() as u64,
+            );
         }
     }
 }
 
+/// Returns the time of the clock in the snapshot.
+fn clock_time(snapshot: &TimeSnapshot, clock_id: ClockId) -> Duration {
+    match clock_id {
+        ClockId::CLOCK_REALTIME | ClockId::CLOCK_REALTIME_COARSE => snapshot.realtime,
+        ClockId::CLOCK_MONOTONIC | ClockId::CLOCK_MONOTONIC_COARSE | ClockId::CLOCK_BOOTTIME => {
+            snapshot.monotonic
+        }
+        ClockId::CLOCK_MONOTONIC_RAW => snapshot.raw,
+        ClockId::CLOCK_TAI => snapshot.tai,
+        _ => unreachable!("the clock is not supported by VDSO"),
+    }
+}
+
 /// Vdso (virtual dynamic shared object) is used to export some safe kernel space routines to user space applications
 /// so that applications can call these kernel space routines in-process, without context switching.
 ///
@@ -195,8 +245,8 @@ impl VdsoData {
 /// and a `Vmo` that contains all VDSO-related information, including the VDSO data and the VDSO calling interfaces.
 /// This `Vmo` must be mapped to every userspace process.
 struct Vdso {
-    /// A `VdsoData` instance.
-    data: SpinLock<VdsoData>,
+    /// The `VdsoData` instances, which are indexed by `CS_HRES` and `CS_RAW`.
+    data: SpinLock<[VdsoData; 2]>,
     /// The VMO of the entire VDSO, including the library text and the VDSO data.
     vmo: Arc<Vmo>,
     /// The `UFrame` that contains the VDSO data. This frame is contained in and
@@ -204,23 +254,26 @@ struct Vdso {
     data_frame: UFrame,
 }
 
-/// A `SpinLock` for the `seq` field in `VdsoData`.
-static SEQ_LOCK: SpinLock<()> = SpinLock::new(());
-
 /// The size of the VDSO VMO.
 pub const VDSO_VMO_SIZE: usize = 5 * PAGE_SIZE;
 
 impl Vdso {
     /// Construct a new `Vdso`, including an initialized `VdsoData` and a VMO of the VDSO.
     fn new() -> Self {
-        let mut vdso_data = VdsoData::empty();
-        vdso_data.init();
+        let mut vdso_data = [VdsoData::empty(); 2];
+        for data in vdso_data.iter_mut() {
+            data.init();
+        }
 
         let (vdso_vmo, data_frame) = {
             let vmo_options = VmoOptions::<Rights>::new(VDSO_VMO_SIZE);
             let vdso_vmo = vmo_options.alloc().unwrap();
             // Write VDSO data to VDSO VMO.
-            vdso_vmo.write_bytes(0x80, vdso_data.as_bytes()).unwrap();
+            for (index, data) in vdso_data.iter().enumerate() {
+                vdso_vmo
+                    .write_bytes(data_offset(index), data.as_bytes())
+                    .unwrap();
+            }
 
             let vdso_lib_vmo = {
                 let vdso_path = FsPath::new(AT_FDCWD, "/lib/x86_64-linux-gnu/vdso64.so").unwrap();
@@ -236,59 +289,74 @@ impl Vdso {
             let data_frame = vdso_vmo.commit_page(0).unwrap();
             (vdso_vmo, data_frame)
         };
-        Self {
+        let vdso = Self {
             data: SpinLock::new(vdso_data),
             vmo: Arc::new(vdso_vmo),
             data_frame,
-        }
+        };
+
+        let (last_instant, last_cycles) = aster_time::default_clocksource().last_record();
+        vdso.update_high_res_instant(last_instant, last_cycles);
+        vdso.update_coarse_res_instant(timekeeping::snapshot());
+        vdso
     }
 
     fn update_high_res_instant(&self, instant: Instant, instant_cycles: u64) {
-        let seq_lock = SEQ_LOCK.lock();
-        self.data
-            .lock()
-            .update_high_res_instant(instant, instant_cycles);
-
-        // Update begins.
-        self.data_frame.write_val(0x80, &1).unwrap();
-        self.data_frame.write_val(0x88, &instant_cycles).unwrap();
-        for clock_id in HIGH_RES_CLOCK_IDS {
-            self.update_data_frame_instant(clock_id);
-        }
+        let raw_mult = aster_time::default_clocksource().coeff().mult();
+        let snapshot = timekeeping::snapshot_at(Duration::new(instant.secs(), instant.nanos()));
+
+        let mut data = self.data.lock();
+        data[CS_HRES].update_high_res_instant(
+            &HIGH_RES_CLOCK_IDS,
+            &snapshot,
+            instant_cycles,
+            timekeeping::slewed_mult(raw_mult),
+        );
+        self.write_data_frame(CS_HRES, &mut data[CS_HRES]);
+        data[CS_RAW].update_high_res_instant(
+            &RAW_CLOCK_IDS,
+            &snapshot,
+            instant_cycles,
+            Some(raw_mult),
+        );
+        self.write_data_frame(CS_RAW, &mut data[CS_RAW]);
+    }
 
-        // Update finishes.
-        self.data_frame.write_val(0x80, &0).unwrap();
+    fn update_coarse_res_instant(&self, snapshot: TimeSnapshot) {
+        let mut data = self.data.lock();
+        data[CS_HRES].update_coarse_res_instant(snapshot);
+        self.write_data_frame(CS_HRES, &mut data[CS_HRES]);
     }
 
-    fn update_coarse_res_instant(&self, instant: Instant) {
-        let seq_lock = SEQ_LOCK.lock();
-        self.data.lock().update_coarse_res_instant(instant);
+    /// Writes the `VdsoData` to the `data_frame`.
+    ///
+    /// The sequence number is odd during the update, so that the VDSO routines retry reading
+    /// the data if they race with the update.
+    fn write_data_frame(&self, index: usize, data: &mut VdsoData) {
+        let offset = data_offset(index);
+        let seq_size = size_of_val(&data.seq);
 
         // Update begins.
-        self.data_frame.write_val(0x80, &1).unwrap();
-        for clock_id in COARSE_RES_CLOCK_IDS {
-            self.update_data_frame_instant(clock_id);
-        }
-
-        // Update finishes.
-        self.data_frame.write_val(0x80, &0).unwrap();
-    }
+        data.seq = data.seq.wrapping_add(1);
+        self.data_frame.write_val(offset, &data.seq).unwrap();
+        fence(Ordering::Release);
 
-    /// Update the requisite fields of the VDSO data in the `data_frame`.
-    fn update_data_frame_instant(&self, clockid: ClockId) {
-        let clock_index = clockid as usize;
-        let secs_offset = 0xA0 + clock_index * 0x10;
-        let nanos_info_offset = 0xA8 + clock_index * 0x10;
-        let data = self.data.lock();
-        self.data_frame
-            .write_val(secs_offset, &data.basetime[clock_index].secs)
-            .unwrap();
         self.data_frame
-            .write_val(nanos_info_offset, &data.basetime[clock_index].nanos_info)
+            .write_bytes(offset + seq_size, &data.as_bytes()[seq_size..])
             .unwrap();
+
+        // Update finishes.
+        fence(Ordering::Release);
+        data.seq = data.seq.wrapping_add(1);
+        self.data_frame.write_val(offset, &data.seq).unwrap();
     }
 }
 
+/// Returns the offset of the `VdsoData` with the index in the VDSO VMO.
+fn data_offset(index: usize) -> usize {
+    VDSO_DATA_OFFSET + index * size_of::<VdsoData>()
+}
+
 /// Update the `VdsoInstant` for clock IDs with high resolution in Vdso.
 fn update_vdso_high_res_instant(instant: Instant, instant_cycles: u64) {
     VDSO.get()
@@ -297,19 +365,25 @@ fn update_vdso_high_res_instant(instant: Instant, instant_cycles: u64) {
 }
 
 /// Update the `VdsoInstant` for clock IDs with coarse resolution in Vdso.
+///
+/// This also refreshes the clock IDs with high resolution, so that the VDSO follows the
+/// changes of the clocks, e.g., when the real-time clock is set or slewed.
 fn update_vdso_coarse_res_instant() {
-    let instant = Instant::from(read_monotonic_time());
-    VDSO.get().unwrap().update_coarse_res_instant(instant);
+    let vdso = VDSO.get().unwrap();
+    vdso.update_coarse_res_instant(timekeeping::snapshot());
+
+    let (last_instant, last_cycles) = aster_time::default_clocksource().last_record();
+    vdso.update_high_res_instant(last_instant, last_cycles);
 }
 
-/// Init `START_SECS_COUNT`, which is used to record the seconds passed since 1970-01-01 00:00:00.
-fn init_start_secs_count() {
-    let time_duration = START_TIME
-        .get()
-        .unwrap()
-        .duration_since(&SystemTime::UNIX_EPOCH)
-        .unwrap();
-    START_SECS_COUNT.call_once(|| time_duration.as_secs());
+/// Refreshes the VDSO data after the clocks are set or adjusted.
+pub(crate) fn on_clock_changed() {
+    if VDSO.get().is_some() {
+        // The VDSO data are also updated in the interrupt context, so disable the local IRQs
+        // to avoid deadlocks.
+        let _irq_guard = ostd::trap::disable_local();
+        update_vdso_coarse_res_instant();
+    }
 }
 
 fn init_vdso() {
@@ -319,7 +393,6 @@ fn init_vdso() {
 
 /// Init this module.
 pub(super) fn init() {
-    init_start_secs_count();
     init_vdso();
     aster_time::VDSO_DATA_HIGH_RES_UPDATE_FN.call_once(|| Arc::new(update_vdso_high_res_instant));
 
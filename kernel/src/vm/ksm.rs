@@ -0,0 +1,352 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! Kernel same-page merging (KSM).
+//!
+//! KSM merges the identical pages in the private anonymous mappings that are marked as mergeable
+//! by `madvise(MADV_MERGEABLE)`. A background thread scans these pages and maps the pages with
+//! the same contents to a single read-only KSM page, which may be shared across processes.
+//! Writing to a merged page triggers a copy-on-write page fault, which gives the writer a private
+//! copy again.
+//!
+//! Like Linux, a page is merged in two steps. When the scanner first sees a page, the page is
+//! recorded as a candidate. When it later sees another page with the same contents, both pages
+//! are replaced by a new KSM page. Then the pages with the same contents as a KSM page are
+//! merged into it directly. The candidates are forgotten after each full scan, since their
+//! contents may change at any time.
+//!
+//! The scanner is controlled by the files in `/proc/sys/vm/ksm`, which correspond to the files
+//! in `/sys/kernel/mm/ksm` on Linux.
+//!
+//! Reference: <https://docs.kernel.org/admin-guide/mm/ksm.html>
+
+use core::{
+    sync::atomic::{AtomicU32, AtomicU64, AtomicUsize, Ordering},
+    time::Duration,
+};
+
+use ostd::{
+    mm::{Paddr, UFrame, MAX_USERSPACE_VADDR},
+    sync::WaitQueue,
+};
+use spin::Once;
+
+use super::util::{duplicate_frame, is_zero_frame};
+use crate::{
+    prelude::*,
+    process::{process_table, Pid, Process},
+    thread::kernel_thread::ThreadOptions,
+};
+
+/// The state of the KSM scanner, which is controlled by `/sys/kernel/mm/ksm/run`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, TryFromInt)]
+#[repr(u32)]
+pub enum KsmRun {
+    /// Stops the scanner but keeps the merged pages.
+    Stop = 0,
+    /// Runs the scanner.
+    Run = 1,
+    /// Stops the scanner and unmerges all the merged pages.
+    Unmerge = 2,
+}
+
+static RUN: AtomicU32 = AtomicU32::new(KsmRun::Stop as u32);
+static PAGES_TO_SCAN: AtomicUsize = AtomicUsize::new(100);
+static SLEEP_MILLISECS: AtomicU64 = AtomicU64::new(20);
+static FULL_SCANS: AtomicU64 = AtomicU64::new(0);
+
+static KSM: Mutex<Ksm> = Mutex::new(Ksm::new());
+static SCANNER_WAIT_QUEUE: WaitQueue = WaitQueue::new();
+static SCANNER: Once<()> = Once::new();
+
+/// Returns the state of the KSM scanner.
+pub fn run() -> KsmRun {
+    KsmRun::try_from(RUN.load(Ordering::Relaxed)).unwrap()
+}
+
+/// Sets the state of the KSM scanner.
+///
+/// If the state is [`KsmRun::Unmerge`], all the merged pages are unmerged before this function
+/// returns.
+pub fn set_run(new_run: KsmRun) -> Result<()> {
+    // Hold the lock so that the scanner cannot merge pages while unmerging them.
+    let mut ksm = KSM.lock();
+
+    RUN.store(new_run as u32, Ordering::Relaxed);
+    match new_run {
+        KsmRun::Stop => {}
+        KsmRun::Run => {
+            SCANNER.call_once(|| {
+                ThreadOptions::new(scanner_loop).name("ksmd").spawn();
+            });
+            SCANNER_WAIT_QUEUE.wake_all();
+        }
+        KsmRun::Unmerge => ksm.unmerge_all()?,
+    }
+
+    Ok(())
+}
+
+/// Returns the maximum number of pages to scan before the scanner sleeps.
+pub fn pages_to_scan() -> usize {
+    PAGES_TO_SCAN.load(Ordering::Relaxed)
+}
+
+/// Sets the maximum number of pages to scan before the scanner sleeps.
+pub fn set_pages_to_scan(pages: usize) {
+    PAGES_TO_SCAN.store(pages, Ordering::Relaxed);
+}
+
+/// Returns the time in milliseconds that the scanner sleeps between scans.
+pub fn sleep_millisecs() -> u64 {
+    SLEEP_MILLISECS.load(Ordering::Relaxed)
+}
+
+/// Sets the time in milliseconds that the scanner sleeps between scans.
+pub fn set_sleep_millisecs(millisecs: u64) {
+    SLEEP_MILLISECS.store(millisecs, Ordering::Relaxed);
+    SCANNER_WAIT_QUEUE.wake_all();
+}
+
+/// Returns how many times all the mergeable pages have been scanned.
+pub fn full_scans() -> u64 {
+    FULL_SCANS.load(Ordering::Relaxed)
+}
+
+/// Returns the number of KSM pages that are in use.
+pub fn pages_shared() -> usize {
+    KSM.lock()
+        .stable_pages()
+        .filter(|page| num_users(page) > 0)
+        .count()
+}
+
+/// Returns the number of extra pages that are saved by sharing the KSM pages.
+pub fn pages_sharing() -> usize {
+    KSM.lock()
+        .stable_pages()
+        .map(|page| num_users(page).saturating_sub(1))
+        .sum()
+}
+
+/// Returns whether the frame is a KSM page.
+pub fn is_ksm_frame(frame: &UFrame) -> bool {
+    KSM.lock().is_ksm_frame(frame)
+}
+
+/// Returns the number of mappings of the KSM page.
+fn num_users(page: &UFrame) -> usize {
+    // One reference is held by the stable tree.
+    page.reference_count() as usize - 1
+}
+
+fn scanner_loop() {
+    loop {
+        if run() != KsmRun::Run {
+            SCANNER_WAIT_QUEUE.wait_until(|| (run() == KsmRun::Run).then_some(()));
+        }
+
+        KSM.lock().scan(pages_to_scan());
+
+        let timeout = Duration::from_millis(sleep_millisecs());
+        let _ = SCANNER_WAIT_QUEUE.wait_until_or_timeout(|| None::<()>, &timeout);
+    }
+}
+
+/// A page that has been seen by the scanner but has not been merged.
+struct Candidate {
+    process: Weak<Process>,
+    addr: Vaddr,
+}
+
+struct Ksm {
+    /// The KSM pages, indexed by the checksums of their contents.
+    stable: BTreeMap<u64, Vec<UFrame>>,
+    /// The physical addresses of the KSM pages.
+    stable_paddrs: BTreeSet<Paddr>,
+    /// The candidates that are seen in the current full scan, indexed by the checksums of their
+    /// contents.
+    unstable: BTreeMap<u64, Candidate>,
+    /// The process and the address where the next scan starts.
+    next_pid: Pid,
+    next_addr: Vaddr,
+}
+
+impl Ksm {
+    const fn new() -> Self {
+        Self {
+            stable: BTreeMap::new(),
+            stable_paddrs: BTreeSet::new(),
+            unstable: BTreeMap::new(),
+            next_pid: 0,
+            next_addr: 0,
+        }
+    }
+
+    fn stable_pages(&self) -> impl Iterator<Item = &UFrame> {
+        self.stable.values().flatten()
+    }
+
+    fn is_ksm_frame(&self, frame: &UFrame) -> bool {
+        self.stable_paddrs.contains(&frame.start_paddr())
+    }
+
+    /// Scans at most `budget` pages, starting from where the last scan stopped.
+    fn scan(&mut self, budget: usize) {
+        let mut remaining = budget;
+
+        while remaining > 0 {
+            let next_process = process_table::process_table_mut()
+                .iter()
+                .find(|process| process.pid() >= self.next_pid)
+                .cloned();
+            let Some(process) = next_process else {
+                // Start the next full scan in the next round, so that the scanner does not spin
+                // if there are few mergeable pages.
+                self.finish_full_scan();
+                break;
+            };
+
+            let root_vmar = process.root_vmar();
+            let (addrs, next_addr) = root_vmar.mergeable_pages(self.next_addr, remaining);
+            remaining -= addrs.len();
+            for addr in addrs {
+                self.merge_page(&process, addr);
+            }
+
+            match next_addr {
+                Some(next_addr) => self.next_addr = next_addr,
+                None => {
+                    self.next_pid = process.pid() + 1;
+                    self.next_addr = 0;
+                }
+            }
+        }
+    }
+
+    fn finish_full_scan(&mut self) {
+        self.unstable.clear();
+
+        // Drop the KSM pages that are no longer mapped.
+        for pages in self.stable.values_mut() {
+            pages.retain(|page| {
+                let is_used = num_users(page) > 0;
+                if !is_used {
+                    self.stable_paddrs.remove(&page.start_paddr());
+                }
+                is_used
+            });
+        }
+        self.stable.retain(|_, pages| !pages.is_empty());
+
+        self.next_pid = 0;
+        self.next_addr = 0;
+        FULL_SCANS.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Tries to merge the page at `addr` of `process`.
+    fn merge_page(&mut self, process: &Arc<Process>, addr: Vaddr) {
+        let root_vmar = process.root_vmar();
+        let mut new_ksm_page = None;
+
+        let _ = root_vmar.replace_mergeable_page(addr, |frame| {
+            if is_zero_frame(frame) || self.is_ksm_frame(frame) {
+                return None;
+            }
+            let checksum = checksum(frame);
+
+            // Merge the page into an existing KSM page.
+            if let Some(pages) = self.stable.get(&checksum) {
+                if let Some(page) = pages.iter().find(|page| is_same_contents(page, frame)) {
+                    return Some(page.clone());
+                }
+            }
+
+            // Merge the page with a candidate if they have the same contents. The candidate is
+            // checked later, since its page cannot be locked at the same time.
+            if let Some(candidate) = self.unstable.remove(&checksum) {
+                let page: UFrame = duplicate_frame(frame, None).ok()?.into();
+                self.stable.entry(checksum).or_default().push(page.clone());
+                self.stable_paddrs.insert(page.start_paddr());
+                new_ksm_page = Some((page.clone(), candidate));
+                return Some(page);
+            }
+
+            self.unstable.insert(
+                checksum,
+                Candidate {
+                    process: Arc::downgrade(process),
+                    addr,
+                },
+            );
+            None
+        });
+
+        let Some((page, candidate)) = new_ksm_page else {
+            return;
+        };
+        let Some(candidate_process) = candidate.process.upgrade() else {
+            return;
+        };
+        // If the candidate has been changed, the new KSM page is dropped at the end of the next
+        // full scan unless other pages are merged into it.
+        let _ = candidate_process
+            .root_vmar()
+            .replace_mergeable_page(candidate.addr, |frame| {
+                (!self.is_ksm_frame(frame) && is_same_contents(&page, frame)).then(|| page.clone())
+            });
+    }
+
+    /// Replaces all the KSM pages with private copies and forgets them.
+    fn unmerge_all(&mut self) -> Result<()> {
+        let processes: Vec<Arc<Process>> =
+            process_table::process_table_mut().iter().cloned().collect();
+        for process in processes {
+            process
+                .root_vmar()
+                .unmerge_pages(0..MAX_USERSPACE_VADDR, |frame| self.is_ksm_frame(frame))?;
+        }
+
+        self.stable.clear();
+        self.stable_paddrs.clear();
+        self.unstable.clear();
+        self.next_pid = 0;
+        self.next_addr = 0;
+
+        Ok(())
+    }
+}
+
+/// Replaces the KSM pages mapped within `range` of `process` with private copies.
+///
+/// This is used to handle `madvise(MADV_UNMERGEABLE)`.
+pub fn unmerge_range(process: &Process, range: Range<Vaddr>) -> Result<()> {
+    let ksm = KSM.lock();
+    process
+        .root_vmar()
+        .unmerge_pages(range, |frame| ksm.is_ksm_frame(frame))
+}
+
+/// Computes the checksum of the contents of the frame with the FNV-1a hash.
+fn checksum(frame: &UFrame) -> u64 {
+    const FNV_OFFSET_BASIS: u64 = 0xcbf2_9ce4_8422_2325;
+    const FNV_PRIME: u64 = 0x0100_0000_01b3;
+
+    let mut reader = frame.reader();
+    let mut hash = FNV_OFFSET_BASIS;
+    while reader.remain() > 0 {
+        let word = reader.read_val::<u64>().unwrap();
+        hash = (hash ^ word).wrapping_mul(FNV_PRIME);
+    }
+    hash
+}
+
+fn is_same_contents(frame1: &UFrame, frame2: &UFrame) -> bool {
+    let mut reader1 = frame1.reader();
+    let mut reader2 = frame2.reader();
+    while reader1.remain() > 0 {
+        if reader1.read_val::<u64>().unwrap() != reader2.read_val::<u64>().unwrap() {
+            return false;
+        }
+    }
+    true
+}
@@ -0,0 +1,213 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! Memory groups, which limit the memory used by groups of processes.
+//!
+//! This implements the memory controller of cgroups. The frames that back the user memory are
+//! charged to the memory group of the process that allocates them, and are uncharged when they
+//! are freed.
+
+use core::sync::atomic::{AtomicUsize, Ordering};
+
+use ostd::{
+    impl_untyped_frame_meta_for,
+    mm::{Frame, FrameAllocOptions},
+};
+use spin::Once;
+
+use super::{
+    mempolicy::{current_memory_policy, MemoryPolicy},
+    swap::check_free_memory,
+    thp::{HUGE_PAGE_LEVEL, HUGE_PAGE_SIZE},
+};
+use crate::{prelude::*, process::posix_thread::AsPosixThread, thread::Thread};
+
+/// A group of processes whose memory usage is limited.
+///
+/// The groups form a hierarchy, and the memory charged to a group is also charged to all the
+/// ancestors of the group.
+#[derive(Debug)]
+pub struct MemoryGroup {
+    parent: Option<Arc<MemoryGroup>>,
+    /// The maximum memory usage in bytes, where `usize::MAX` means no limits.
+    max: AtomicUsize,
+    /// The current memory usage in bytes.
+    current: AtomicUsize,
+}
+
+impl MemoryGroup {
+    /// Returns the root group, which contains all the processes that are not in other groups.
+    ///
+    /// The memory usage of the root group is not limited nor tracked.
+    pub fn root() -> &'static Arc<MemoryGroup> {
+        static ROOT: Once<Arc<MemoryGroup>> = Once::new();
+
+        ROOT.call_once(|| Arc::new(Self::new(None)))
+    }
+
+    /// Creates a child group without limits.
+    pub fn new_child(self: &Arc<Self>) -> Arc<Self> {
+        Arc::new(Self::new(Some(self.clone())))
+    }
+
+    fn new(parent: Option<Arc<MemoryGroup>>) -> Self {
+        Self {
+            parent,
+            max: AtomicUsize::new(usize::MAX),
+            current: AtomicUsize::new(0),
+        }
+    }
+
+    /// Returns the maximum memory usage in bytes, where `None` means no limits.
+    pub fn max(&self) -> Option<usize> {
+        let max = self.max.load(Ordering::Relaxed);
+        (max != usize::MAX).then_some(max)
+    }
+
+    /// Sets the maximum memory usage in bytes, where `None` means no limits.
+    ///
+    /// The memory that is already charged is not reclaimed, even if it exceeds the new limit.
+    //
+    // TODO: Reclaim the memory or invoke the OOM killer if the usage exceeds the new limit.
+    pub fn set_max(&self, max: Option<usize>) {
+        self.max.store(max.unwrap_or(usize::MAX), Ordering::Relaxed);
+    }
+
+    /// Returns the current memory usage in bytes.
+    pub fn current(&self) -> usize {
+        self.current.load(Ordering::Relaxed)
+    }
+
+    fn ancestors(&self) -> impl Iterator<Item = &MemoryGroup> {
+        core::iter::successors(Some(self), |group| group.parent.as_deref())
+            .take_while(|group| group.parent.is_some())
+    }
+
+    /// Charges `size` bytes to the group and its ancestors.
+    //
+    // TODO: Reclaim the memory or invoke the OOM killer instead of failing immediately.
+    fn try_charge(&self, size: usize) -> Result<()> {
+        for (charged, group) in self.ancestors().enumerate() {
+            let max = group.max.load(Ordering::Relaxed);
+            let result =
+                group
+                    .current
+                    .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |current| {
+                        current.checked_add(size).filter(|new| *new <= max)
+                    });
+
+            if result.is_err() {
+                self.ancestors()
+                    .take(charged)
+                    .for_each(|group| group.uncharge_one(size));
+                return_errno_with_message!(
+                    Errno::ENOMEM,
+                    "the memory usage exceeds the limit of the memory group"
+                );
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Uncharges `size` bytes from the group and its ancestors.
+    fn uncharge(&self, size: usize) {
+        self.ancestors().for_each(|group| group.uncharge_one(size));
+    }
+
+    fn uncharge_one(&self, size: usize) {
+        self.current.fetch_sub(size, Ordering::Relaxed);
+    }
+}
+
+/// The metadata of a frame that is charged to a memory group.
+#[derive(Debug)]
+pub struct ChargedFrameMeta {
+    group: Option<Arc<MemoryGroup>>,
+    /// The number of bytes that are charged.
+    size: usize,
+}
+
+impl ChargedFrameMeta {
+    /// Moves the charge of a base page to the metadata of a new base page.
+    ///
+    /// This is used to split a huge frame with [`Frame::split`], where the metadata of the huge
+    /// frame is kept by its first base page.
+    pub fn split_base_page(&mut self) -> Self {
+        self.size -= PAGE_SIZE;
+        Self {
+            group: self.group.clone(),
+            size: PAGE_SIZE,
+        }
+    }
+}
+
+impl Drop for ChargedFrameMeta {
+    fn drop(&mut self) {
+        if let Some(group) = self.group.take() {
+            group.uncharge(self.size);
+        }
+    }
+}
+
+impl_untyped_frame_meta_for!(ChargedFrameMeta);
+
+/// Allocates a frame for the user memory and charges it to the memory group of the current
+/// process.
+///
+/// The frame is allocated according to the memory `policy`, or the memory policy of the current
+/// thread if `policy` is `None`. If the free memory is low, the pages that are not recently used
+/// will be swapped out in the background.
+pub fn alloc_user_frame(
+    options: &FrameAllocOptions,
+    policy: Option<&MemoryPolicy>,
+) -> Result<Frame<ChargedFrameMeta>> {
+    alloc_charged(options, policy, PAGE_SIZE, |options, meta| {
+        options.alloc_frame_with(meta)
+    })
+}
+
+/// Allocates a huge frame for the user memory and charges it to the memory group of the current
+/// process.
+///
+/// This is the same as [`alloc_user_frame`], except that the frame is a huge frame of
+/// [`HUGE_PAGE_SIZE`] bytes.
+pub fn alloc_user_huge_frame(
+    options: &FrameAllocOptions,
+    policy: Option<&MemoryPolicy>,
+) -> Result<Frame<ChargedFrameMeta>> {
+    alloc_charged(options, policy, HUGE_PAGE_SIZE, |options, meta| {
+        options.alloc_huge_frame_with(HUGE_PAGE_LEVEL, meta)
+    })
+}
+
+fn alloc_charged(
+    options: &FrameAllocOptions,
+    policy: Option<&MemoryPolicy>,
+    size: usize,
+    alloc: impl FnOnce(&FrameAllocOptions, ChargedFrameMeta) -> ostd::Result<Frame<ChargedFrameMeta>>,
+) -> Result<Frame<ChargedFrameMeta>> {
+    check_free_memory();
+
+    let group = current_group();
+    if let Some(group) = &group {
+        group.try_charge(size)?;
+    }
+
+    let mut options = options.clone();
+    match policy {
+        Some(policy) => policy.apply(&mut options),
+        None => current_memory_policy().apply(&mut options),
+    }
+
+    // If the allocation fails, the charge is reverted when the metadata is dropped.
+    let meta = ChargedFrameMeta { group, size };
+    Ok(alloc(&options, meta)?)
+}
+
+fn current_group() -> Option<Arc<MemoryGroup>> {
+    let thread = Thread::current()?;
+    let process = thread.as_posix_thread()?.weak_process().upgrade()?;
+    let group = process.cgroup().effective_memory_group().clone();
+
+    (!Arc::ptr_eq(&group, MemoryGroup::root())).then_some(group)
+}
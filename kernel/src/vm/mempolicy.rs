@@ -0,0 +1,218 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! NUMA memory policies.
+//!
+//! A memory policy determines the NUMA nodes that the frames of the user memory are allocated
+//! from. Each thread has a memory policy that is set by `set_mempolicy`. A mapping can have its
+//! own memory policy that is set by `mbind`, which overrides the policy of the thread for the
+//! anonymous pages that are allocated when handling the page faults in the mapping.
+//!
+//! Without a memory policy, the frames are allocated from the node of the CPU that first
+//! touches the page, falling back to other nodes if that node runs out of memory.
+//!
+//! Reference: <https://elixir.bootlin.com/linux/v6.0.9/source/mm/mempolicy.c>
+
+use core::sync::atomic::{AtomicUsize, Ordering};
+
+use ostd::mm::{numa::NodeSet, FrameAllocOptions};
+
+use crate::{prelude::*, process::posix_thread::AsPosixThread, thread::Thread};
+
+/// The modes of memory policies.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, TryFromInt)]
+#[repr(u32)]
+pub enum MemoryPolicyMode {
+    /// Uses the default policy, i.e., the policy of the thread or the local allocation.
+    Default = 0,
+    /// Allocates from the preferred node, falling back to other nodes.
+    Preferred = 1,
+    /// Allocates only from the nodes in the node set.
+    Bind = 2,
+    /// Allocates from the nodes in the node set in turn.
+    Interleave = 3,
+    /// Allocates from the node of the current CPU.
+    Local = 4,
+}
+
+bitflags! {
+    /// The mode flags of memory policies.
+    pub struct MemoryPolicyFlags: u32 {
+        /// The node set is not remapped when the allowed nodes change.
+        const STATIC_NODES   = 1 << 15;
+        /// The node set is relative to the allowed nodes.
+        const RELATIVE_NODES = 1 << 14;
+    }
+}
+
+/// A memory policy.
+#[derive(Debug)]
+pub struct MemoryPolicy {
+    mode: MemoryPolicyMode,
+    flags: MemoryPolicyFlags,
+    /// The nodes specified by the user.
+    user_nodes: NodeSet,
+    /// The nodes that the frames are allocated from.
+    nodes: NodeSet,
+    /// The index of the next node that is used in the interleave mode.
+    next_interleave: AtomicUsize,
+}
+
+impl MemoryPolicy {
+    /// Creates the default memory policy.
+    pub fn new_default() -> Arc<Self> {
+        Arc::new(Self {
+            mode: MemoryPolicyMode::Default,
+            flags: MemoryPolicyFlags::empty(),
+            user_nodes: NodeSet::new_empty(),
+            nodes: NodeSet::new_empty(),
+            next_interleave: AtomicUsize::new(0),
+        })
+    }
+
+    /// Creates a memory policy with `mode`, `flags` and the nodes specified by the user.
+    ///
+    /// The nodes that do not exist in the system are ignored.
+    ///
+    /// # Errors
+    ///
+    /// This method returns an error with [`EINVAL`] if the nodes are not valid for the mode.
+    ///
+    /// [`EINVAL`]: Errno::EINVAL
+    pub fn new(
+        mode: MemoryPolicyMode,
+        flags: MemoryPolicyFlags,
+        user_nodes: NodeSet,
+    ) -> Result<Arc<Self>> {
+        if flags.contains(MemoryPolicyFlags::STATIC_NODES | MemoryPolicyFlags::RELATIVE_NODES) {
+            return_errno_with_message!(
+                Errno::EINVAL,
+                "the static and relative flags cannot be specified at the same time"
+            );
+        }
+
+        let mut mode = mode;
+        match mode {
+            MemoryPolicyMode::Default => {
+                if !user_nodes.is_empty() {
+                    return_errno_with_message!(
+                        Errno::EINVAL,
+                        "the default policy does not accept nodes"
+                    );
+                }
+                return Ok(Self::new_default());
+            }
+            MemoryPolicyMode::Local => {
+                if !user_nodes.is_empty() || !flags.is_empty() {
+                    return_errno_with_message!(
+                        Errno::EINVAL,
+                        "the local policy does not accept nodes or flags"
+                    );
+                }
+            }
+            MemoryPolicyMode::Preferred if user_nodes.is_empty() => {
+                // Like Linux, an empty preferred node set means the local allocation.
+                if !flags.is_empty() {
+                    return_errno_with_message!(
+                        Errno::EINVAL,
+                        "the local preferred policy does not accept flags"
+                    );
+                }
+                mode = MemoryPolicyMode::Local;
+            }
+            MemoryPolicyMode::Preferred | MemoryPolicyMode::Bind | MemoryPolicyMode::Interleave => {
+                if user_nodes.is_empty() {
+                    return_errno_with_message!(Errno::EINVAL, "the node set is empty");
+                }
+            }
+        }
+
+        let allowed_nodes = NodeSet::new_full();
+        let mut nodes = if flags.contains(MemoryPolicyFlags::RELATIVE_NODES) {
+            // Node `i` means the `i`-th allowed node, wrapping around.
+            let allowed: Vec<usize> = allowed_nodes.iter().collect();
+            let mut nodes = NodeSet::new_empty();
+            user_nodes
+                .iter()
+                .for_each(|node| nodes.add(allowed[node % allowed.len()]));
+            nodes
+        } else {
+            user_nodes.intersection(&allowed_nodes)
+        };
+        if mode != MemoryPolicyMode::Local && nodes.is_empty() {
+            return_errno_with_message!(Errno::EINVAL, "the nodes do not exist");
+        }
+        if mode == MemoryPolicyMode::Preferred {
+            // Only the first node is preferred.
+            let first_node = nodes.iter().next().unwrap();
+            nodes = NodeSet::new_empty();
+            nodes.add(first_node);
+        }
+
+        Ok(Arc::new(Self {
+            mode,
+            flags,
+            user_nodes,
+            nodes,
+            next_interleave: AtomicUsize::new(0),
+        }))
+    }
+
+    /// Returns the mode of the memory policy.
+    pub fn mode(&self) -> MemoryPolicyMode {
+        self.mode
+    }
+
+    /// Returns the mode flags of the memory policy.
+    pub fn flags(&self) -> MemoryPolicyFlags {
+        self.flags
+    }
+
+    /// Returns the nodes that are reported to the user.
+    pub fn user_visible_nodes(&self) -> NodeSet {
+        match self.mode {
+            MemoryPolicyMode::Default | MemoryPolicyMode::Local => NodeSet::new_empty(),
+            _ if self.flags.is_empty() => self.nodes,
+            _ => self.user_nodes,
+        }
+    }
+
+    /// Returns the node that will be used by the next allocation in the interleave mode.
+    pub fn next_interleave_node(&self) -> Option<usize> {
+        if self.mode != MemoryPolicyMode::Interleave {
+            return None;
+        }
+
+        let index = self.next_interleave.load(Ordering::Relaxed);
+        self.nodes.iter().nth(index % self.nodes.count())
+    }
+
+    /// Applies the memory policy to the options for allocating frames.
+    pub fn apply(&self, options: &mut FrameAllocOptions) {
+        match self.mode {
+            // The frame allocator prefers the node of the current CPU by default.
+            MemoryPolicyMode::Default | MemoryPolicyMode::Local => {}
+            MemoryPolicyMode::Preferred => {
+                options.preferred_node(self.nodes.iter().next().unwrap());
+            }
+            MemoryPolicyMode::Bind => {
+                options.nodes(self.nodes);
+            }
+            MemoryPolicyMode::Interleave => {
+                let index = self.next_interleave.fetch_add(1, Ordering::Relaxed);
+                let node = self.nodes.iter().nth(index % self.nodes.count()).unwrap();
+                options.preferred_node(node);
+            }
+        }
+    }
+}
+
+/// Returns the memory policy of the current thread.
+pub fn current_memory_policy() -> Arc<MemoryPolicy> {
+    Thread::current()
+        .and_then(|thread| {
+            thread
+                .as_posix_thread()
+                .map(|posix_thread| posix_thread.memory_policy().lock().clone())
+        })
+        .unwrap_or_else(MemoryPolicy::new_default)
+}
@@ -16,8 +16,15 @@
 //! In Asterinas, VMARs and VMOs, as well as other capabilities, are implemented
 //! as zero-cost capabilities.
 
+pub mod ksm;
+pub mod memcg;
+pub mod mempolicy;
 pub mod page_fault_handler;
 pub mod perms;
+pub mod swap;
+pub mod thp;
+pub mod userfaultfd;
 pub mod util;
 pub mod vmar;
 pub mod vmo;
+pub mod wx;
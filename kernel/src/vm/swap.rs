@@ -0,0 +1,460 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! Swap areas and the reclamation of anonymous pages.
+//!
+//! A swap area is a file that is prepared by `mkswap` and activated by `swapon`. The active swap
+//! areas are listed in `/proc/swaps`.
+//!
+//! When the free memory is low, a background thread (`kswapd`) scans the private anonymous
+//! mappings of all processes, writes the pages that are not recently used to the swap areas, and
+//! unmaps them. Like the inactive list of Linux, the pages that are not recently used are found
+//! with the accessed bits of the page tables: a page that has been accessed since the last scan
+//! gets a second chance, and its accessed bit is cleared. The thread stops when the free memory
+//! is above the high watermark. The pages can also be swapped out by `madvise(MADV_PAGEOUT)`.
+//!
+//! A swapped-out page is recorded in its mapping as a [`SwapSlot`], which is shared by the
+//! forked processes and is freed when the last of them drops it. The page is read back when it
+//! is accessed again, or when the swap area is deactivated by `swapoff`.
+//!
+//! Reference: <https://docs.kernel.org/admin-guide/mm/concepts.html#reclaim>
+
+use core::{
+    fmt,
+    mem::size_of,
+    sync::atomic::{AtomicBool, AtomicUsize, Ordering},
+    time::Duration,
+};
+
+use id_alloc::IdAlloc;
+use ostd::{mm::stat, sync::WaitQueue};
+use spin::Once;
+
+use crate::{
+    fs::{path::Dentry, utils::InodeType},
+    prelude::*,
+    process::{process_table, Pid, Process},
+    thread::{kernel_thread::ThreadOptions, Thread},
+};
+
+/// An active swap area.
+pub struct SwapArea {
+    dentry: Dentry,
+    /// The number of pages that can be used to store the swapped-out pages.
+    ///
+    /// The first page is the header, which is not counted.
+    nr_pages: usize,
+    priority: i16,
+    /// The allocator of the slots, where slot `i` is stored in page `i + 1` of the swap file.
+    ///
+    /// The slots may be freed with the page tables locked, so a spin lock is used.
+    slots: SpinLock<IdAlloc>,
+    nr_used: AtomicUsize,
+}
+
+impl SwapArea {
+    /// Returns the dentry of the swap file.
+    pub fn dentry(&self) -> &Dentry {
+        &self.dentry
+    }
+
+    /// Returns the size of the swap area in bytes.
+    pub fn size(&self) -> usize {
+        self.nr_pages * PAGE_SIZE
+    }
+
+    /// Returns the size of the swapped-out pages in bytes.
+    pub fn used(&self) -> usize {
+        self.nr_used.load(Ordering::Relaxed) * PAGE_SIZE
+    }
+
+    /// Returns the priority of the swap area.
+    pub fn priority(&self) -> i16 {
+        self.priority
+    }
+
+    fn is_backed_by(&self, dentry: &Dentry) -> bool {
+        Arc::ptr_eq(self.dentry.inode(), dentry.inode())
+    }
+
+    fn alloc_slot(self: &Arc<Self>) -> Option<SwapSlot> {
+        let index = self.slots.lock().alloc()?;
+        self.nr_used.fetch_add(1, Ordering::Relaxed);
+        Some(SwapSlot {
+            area: self.clone(),
+            index,
+        })
+    }
+}
+
+/// A slot in a swap area, which stores a swapped-out page.
+///
+/// The slot is freed when it is dropped.
+pub struct SwapSlot {
+    area: Arc<SwapArea>,
+    index: usize,
+}
+
+impl SwapSlot {
+    /// Returns the swap area where the slot is.
+    pub fn area(&self) -> &Arc<SwapArea> {
+        &self.area
+    }
+
+    /// Writes the page from `reader` to the slot.
+    pub fn write(&self, reader: &mut VmReader) -> Result<()> {
+        let inode = self.area.dentry.inode();
+        if inode.write_direct_at(self.offset(), reader)? != PAGE_SIZE {
+            return_errno_with_message!(Errno::EIO, "the page is not fully written");
+        }
+        Ok(())
+    }
+
+    /// Reads the page from the slot to `writer`.
+    pub fn read(&self, writer: &mut VmWriter) -> Result<()> {
+        let inode = self.area.dentry.inode();
+        if inode.read_direct_at(self.offset(), writer)? != PAGE_SIZE {
+            return_errno_with_message!(Errno::EIO, "the page is not fully read");
+        }
+        Ok(())
+    }
+
+    fn offset(&self) -> usize {
+        (self.index + 1) * PAGE_SIZE
+    }
+}
+
+impl fmt::Debug for SwapSlot {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SwapSlot")
+            .field("priority", &self.area.priority)
+            .field("index", &self.index)
+            .finish()
+    }
+}
+
+impl Drop for SwapSlot {
+    fn drop(&mut self) {
+        self.area.slots.lock().free(self.index);
+        self.area.nr_used.fetch_sub(1, Ordering::Relaxed);
+    }
+}
+
+static SWAP_AREAS: Mutex<SwapAreas> = Mutex::new(SwapAreas {
+    areas: Vec::new(),
+    least_priority: 0,
+});
+
+/// Whether there are active swap areas, which can be checked without sleeping.
+static HAS_SWAP_AREAS: AtomicBool = AtomicBool::new(false);
+
+struct SwapAreas {
+    areas: Vec<Arc<SwapArea>>,
+    /// The priority of the last activated swap area without a specified priority.
+    least_priority: i16,
+}
+
+impl SwapAreas {
+    fn push(&mut self, area: Arc<SwapArea>) {
+        self.areas.push(area);
+        HAS_SWAP_AREAS.store(true, Ordering::Relaxed);
+    }
+
+    fn remove(&mut self, index: usize) -> Arc<SwapArea> {
+        let area = self.areas.remove(index);
+        HAS_SWAP_AREAS.store(!self.areas.is_empty(), Ordering::Relaxed);
+        area
+    }
+}
+
+/// Activates the swap file at `dentry`.
+///
+/// If `priority` is `None`, the swap area has a lower priority than all the swap areas that are
+/// activated before without specified priorities.
+pub fn swap_on(dentry: Dentry, priority: Option<i16>) -> Result<()> {
+    match dentry.type_() {
+        InodeType::File => (),
+        InodeType::Dir => return_errno_with_message!(Errno::EISDIR, "the file is a directory"),
+        _ => return_errno_with_message!(Errno::EINVAL, "only regular files can be used for swap"),
+    }
+
+    let mut swap_areas = SWAP_AREAS.lock();
+    if swap_areas
+        .areas
+        .iter()
+        .any(|area| area.is_backed_by(&dentry))
+    {
+        return_errno_with_message!(Errno::EBUSY, "the file is already used for swap");
+    }
+
+    let nr_pages = read_header(&dentry)?;
+
+    let priority = priority.unwrap_or_else(|| {
+        swap_areas.least_priority -= 1;
+        swap_areas.least_priority
+    });
+    swap_areas.push(Arc::new(SwapArea {
+        dentry,
+        nr_pages,
+        priority,
+        slots: SpinLock::new(IdAlloc::with_capacity(nr_pages)),
+        nr_used: AtomicUsize::new(0),
+    }));
+
+    KSWAPD.call_once(|| {
+        ThreadOptions::new(kswapd_loop).name("kswapd").spawn();
+    });
+
+    Ok(())
+}
+
+/// Deactivates the swap file at `dentry`.
+///
+/// The pages stored in the swap area are swapped in before this function returns. If they
+/// cannot be swapped in (e.g., because the memory is exhausted), the swap area is kept active.
+pub fn swap_off(dentry: &Dentry) -> Result<()> {
+    let area = {
+        let mut swap_areas = SWAP_AREAS.lock();
+        let Some(index) = swap_areas
+            .areas
+            .iter()
+            .position(|area| area.is_backed_by(dentry))
+        else {
+            return_errno_with_message!(Errno::EINVAL, "the file is not used for swap");
+        };
+        // No more slots will be allocated from the swap area.
+        swap_areas.remove(index)
+    };
+
+    if let Err(err) = swap_in_all(&area) {
+        SWAP_AREAS.lock().push(area);
+        return Err(err);
+    }
+
+    Ok(())
+}
+
+/// Swaps in all the pages stored in `area`.
+fn swap_in_all(area: &Arc<SwapArea>) -> Result<()> {
+    // The slots that are being written may be recorded in the mappings after the mappings are
+    // scanned, and the forked processes may be added to the process table after it is scanned.
+    // So the processes are scanned until all the slots are freed.
+    const MAX_SCANS: usize = 100;
+
+    for _ in 0..MAX_SCANS {
+        if area.used() == 0 {
+            return Ok(());
+        }
+
+        let processes: Vec<Arc<Process>> =
+            process_table::process_table_mut().iter().cloned().collect();
+        for process in processes {
+            process.root_vmar().swap_in_area(area)?;
+        }
+
+        if area.used() != 0 {
+            Thread::yield_now();
+        }
+    }
+
+    return_errno_with_message!(Errno::EBUSY, "the swap area is still in use");
+}
+
+/// Returns the active swap areas in the order they are activated.
+pub fn swap_areas() -> Vec<Arc<SwapArea>> {
+    SWAP_AREAS.lock().areas.clone()
+}
+
+/// Allocates a slot from the active swap area with the highest priority that has free slots.
+pub(super) fn alloc_slot() -> Option<SwapSlot> {
+    let mut areas = swap_areas();
+    // The sort is stable, so the swap areas with the same priority are used in the order they
+    // are activated.
+    areas.sort_by_key(|area| core::cmp::Reverse(area.priority));
+    areas.iter().find_map(|area| area.alloc_slot())
+}
+
+/// The number of pages that `kswapd` reclaims in a round, which is the same as
+/// `SWAP_CLUSTER_MAX` of Linux.
+const RECLAIM_BATCH: usize = 32;
+
+static RECLAIMER: Mutex<Reclaimer> = Mutex::new(Reclaimer::new());
+static KSWAPD: Once<()> = Once::new();
+static KSWAPD_WAIT_QUEUE: WaitQueue = WaitQueue::new();
+/// The wait queue of the threads that wait for `kswapd` to finish a round.
+static RECLAIM_WAIT_QUEUE: WaitQueue = WaitQueue::new();
+
+/// Whether `kswapd` should reclaim pages.
+///
+/// This is set when the free memory falls below the low watermark, and is cleared when the free
+/// memory rises above the high watermark.
+static IS_RECLAIMING: AtomicBool = AtomicBool::new(false);
+static NR_ROUNDS: AtomicUsize = AtomicUsize::new(0);
+static NR_RECLAIMED: AtomicUsize = AtomicUsize::new(0);
+
+/// Returns the low watermark of the free memory in bytes.
+fn low_watermark() -> usize {
+    stat::mem_total() / 64
+}
+
+/// Returns the high watermark of the free memory in bytes.
+fn high_watermark() -> usize {
+    low_watermark() * 2
+}
+
+/// Wakes up `kswapd` if the free memory is below the low watermark.
+///
+/// This function never sleeps, so it can be called with the page tables locked.
+pub(super) fn check_free_memory() {
+    if !HAS_SWAP_AREAS.load(Ordering::Relaxed) || IS_RECLAIMING.load(Ordering::Relaxed) {
+        return;
+    }
+
+    if stat::mem_available() < low_watermark() {
+        IS_RECLAIMING.store(true, Ordering::Relaxed);
+        KSWAPD_WAIT_QUEUE.wake_all();
+    }
+}
+
+/// Waits for `kswapd` to reclaim pages after the memory is exhausted.
+///
+/// This method returns whether some pages have been reclaimed, in which case the failed
+/// allocation can be retried.
+pub fn wait_for_reclaim() -> bool {
+    const TIMEOUT: Duration = Duration::from_secs(1);
+
+    if !HAS_SWAP_AREAS.load(Ordering::Relaxed) {
+        return false;
+    }
+
+    let nr_rounds = NR_ROUNDS.load(Ordering::Relaxed);
+    let nr_reclaimed = NR_RECLAIMED.load(Ordering::Relaxed);
+
+    IS_RECLAIMING.store(true, Ordering::Relaxed);
+    KSWAPD_WAIT_QUEUE.wake_all();
+    let _ = RECLAIM_WAIT_QUEUE.wait_until_or_timeout(
+        || (NR_ROUNDS.load(Ordering::Relaxed) != nr_rounds).then_some(()),
+        &TIMEOUT,
+    );
+
+    NR_RECLAIMED.load(Ordering::Relaxed) != nr_reclaimed
+}
+
+fn kswapd_loop() {
+    const BACKOFF: Duration = Duration::from_millis(100);
+
+    loop {
+        KSWAPD_WAIT_QUEUE.wait_until(|| IS_RECLAIMING.load(Ordering::Relaxed).then_some(()));
+
+        let nr_reclaimed = RECLAIMER.lock().reclaim(RECLAIM_BATCH);
+        NR_RECLAIMED.fetch_add(nr_reclaimed, Ordering::Relaxed);
+        NR_ROUNDS.fetch_add(1, Ordering::Relaxed);
+        RECLAIM_WAIT_QUEUE.wake_all();
+
+        if nr_reclaimed == 0 {
+            // Nothing can be reclaimed for now (e.g., the swap areas are full), so back off
+            // instead of scanning the pages again and again.
+            let _ = KSWAPD_WAIT_QUEUE.wait_until_or_timeout(|| None::<()>, &BACKOFF);
+        }
+
+        if !HAS_SWAP_AREAS.load(Ordering::Relaxed) || stat::mem_available() >= high_watermark() {
+            IS_RECLAIMING.store(false, Ordering::Relaxed);
+        }
+    }
+}
+
+/// The state of `kswapd`, which scans the pages of all processes in turn like a clock hand.
+struct Reclaimer {
+    /// The process and the address where the next scan starts.
+    next_pid: Pid,
+    next_addr: Vaddr,
+}
+
+impl Reclaimer {
+    const fn new() -> Self {
+        Self {
+            next_pid: 0,
+            next_addr: 0,
+        }
+    }
+
+    /// Swaps out at most `nr_to_reclaim` pages that are not recently used, starting from where
+    /// the last scan stopped.
+    ///
+    /// Returns the number of the swapped-out pages.
+    fn reclaim(&mut self, nr_to_reclaim: usize) -> usize {
+        let mut nr_reclaimed = 0;
+        // The processes are scanned at most twice, so that the pages whose accessed bits are
+        // cleared in the first scan can be swapped out in the second scan.
+        let mut nr_wraps = 0;
+
+        while nr_wraps < 2 {
+            let next_process = process_table::process_table_mut()
+                .iter()
+                .find(|process| process.pid() >= self.next_pid)
+                .cloned();
+            let Some(process) = next_process else {
+                self.next_pid = 0;
+                self.next_addr = 0;
+                nr_wraps += 1;
+                continue;
+            };
+
+            let root_vmar = process.root_vmar();
+            let (addrs, next_addr) = root_vmar.swappable_pages(self.next_addr, RECLAIM_BATCH);
+            for addr in addrs {
+                self.next_addr = addr + PAGE_SIZE;
+                match root_vmar.swap_out_page(addr) {
+                    Ok(true) => nr_reclaimed += 1,
+                    Ok(false) => {}
+                    // The swap areas are full or cannot be written.
+                    Err(_) => return nr_reclaimed,
+                }
+                if nr_reclaimed == nr_to_reclaim {
+                    return nr_reclaimed;
+                }
+            }
+
+            if next_addr.is_none() {
+                self.next_pid = process.pid() + 1;
+                self.next_addr = 0;
+            }
+        }
+
+        nr_reclaimed
+    }
+}
+
+/// Reads the header of the swap file and returns the number of usable pages.
+///
+/// Reference: <https://elixir.bootlin.com/linux/v6.0.9/source/include/linux/swap.h#L96>
+fn read_header(dentry: &Dentry) -> Result<usize> {
+    const MAGIC: &[u8] = b"SWAPSPACE2";
+    const VERSION_OFFSET: usize = 1024;
+    const LAST_PAGE_OFFSET: usize = 1028;
+
+    let inode = dentry.inode();
+    let mut header = vec![0u8; PAGE_SIZE];
+    if inode.read_bytes_at(0, &mut header)? != PAGE_SIZE
+        || &header[PAGE_SIZE - MAGIC.len()..] != MAGIC
+    {
+        return_errno_with_message!(Errno::EINVAL, "the swap signature is not found");
+    }
+
+    let read_u32 = |offset: usize| {
+        u32::from_ne_bytes(
+            header[offset..offset + size_of::<u32>()]
+                .try_into()
+                .unwrap(),
+        )
+    };
+    if read_u32(VERSION_OFFSET) != 1 {
+        return_errno_with_message!(Errno::EINVAL, "the swap version is not supported");
+    }
+
+    let last_page = read_u32(LAST_PAGE_OFFSET) as usize;
+    if last_page == 0 || inode.size() / PAGE_SIZE <= last_page {
+        return_errno_with_message!(Errno::EINVAL, "the swap area size is invalid");
+    }
+
+    Ok(last_page)
+}
@@ -0,0 +1,157 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! Transparent huge pages (THP).
+//!
+//! The private anonymous mappings are backed by huge pages when possible, which reduces the TLB
+//! misses of the programs that use a lot of memory. A huge page is allocated when a write page
+//! fault occurs in a huge-page-aligned block that is entirely within such a mapping. Like
+//! `khugepaged` on Linux, a background thread also collapses the base pages mapped in such blocks
+//! into huge pages.
+//!
+//! A huge page is split into base pages when only part of it is affected, e.g., when part of it
+//! is unmapped or protected, or when one of its pages is swapped out or pinned.
+//!
+//! Reference: <https://docs.kernel.org/admin-guide/mm/transhuge.html>
+
+use core::time::Duration;
+
+use ostd::{
+    mm::{frame::meta::AnyFrameMeta, Frame, FrameAllocOptions, PagingLevel, UFrame, UntypedMem},
+    sync::WaitQueue,
+};
+
+use super::{
+    memcg::{alloc_user_frame, ChargedFrameMeta},
+    mempolicy::MemoryPolicy,
+};
+use crate::{
+    prelude::*,
+    process::{process_table, Pid},
+    thread::kernel_thread::ThreadOptions,
+};
+
+/// The paging level of the huge pages.
+pub const HUGE_PAGE_LEVEL: PagingLevel = 2;
+/// The size of the huge pages.
+pub const HUGE_PAGE_SIZE: usize = 512 * PAGE_SIZE;
+
+/// The advice on whether a mapping should be backed by huge pages.
+///
+/// The advice is given by `madvise(MADV_HUGEPAGE)` or `madvise(MADV_NOHUGEPAGE)`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HugePageAdvice {
+    /// No advice is given.
+    None,
+    /// The mapping should be backed by huge pages.
+    ///
+    /// Since the eligible mappings are always backed by huge pages when possible, this is the
+    /// same as [`Self::None`] except that it is reported in `/proc/[pid]/smaps`.
+    HugePage,
+    /// The mapping should not be backed by huge pages.
+    ///
+    /// No new huge pages are mapped in the mapping, but the existing ones are kept.
+    NoHugePage,
+}
+
+/// The maximum number of base pages that are not mapped in a block that is collapsed.
+///
+/// This is the default value of `max_ptes_none` on Linux.
+pub(super) const MAX_PTES_NONE: usize = 511;
+
+/// The maximum number of base pages to scan before the collapser sleeps.
+const PAGES_TO_SCAN: usize = 4096;
+/// The time that the collapser sleeps between scans.
+const SCAN_SLEEP: Duration = Duration::from_secs(10);
+
+static COLLAPSER_WAIT_QUEUE: WaitQueue = WaitQueue::new();
+
+pub(crate) fn init() {
+    ThreadOptions::new(collapser_loop)
+        .name("khugepaged")
+        .spawn();
+}
+
+fn collapser_loop() {
+    let mut collapser = Collapser {
+        next_pid: 0,
+        next_addr: 0,
+    };
+
+    loop {
+        collapser.scan(PAGES_TO_SCAN);
+
+        let _ = COLLAPSER_WAIT_QUEUE.wait_until_or_timeout(|| None::<()>, &SCAN_SLEEP);
+    }
+}
+
+struct Collapser {
+    /// The process and the address where the next scan starts.
+    next_pid: Pid,
+    next_addr: Vaddr,
+}
+
+impl Collapser {
+    /// Scans at most `budget` base pages, starting from where the last scan stopped.
+    fn scan(&mut self, budget: usize) {
+        let mut remaining = budget;
+
+        while remaining > 0 {
+            let next_process = process_table::process_table_mut()
+                .iter()
+                .find(|process| process.pid() >= self.next_pid)
+                .cloned();
+            let Some(process) = next_process else {
+                // Start the next full scan in the next round.
+                self.next_pid = 0;
+                self.next_addr = 0;
+                break;
+            };
+
+            let root_vmar = process.root_vmar();
+            let (nr_scanned, next_addr) = root_vmar.collapse_huge_pages(self.next_addr, remaining);
+            remaining = remaining.saturating_sub(nr_scanned);
+
+            match next_addr {
+                Some(next_addr) => self.next_addr = next_addr,
+                None => {
+                    self.next_pid = process.pid() + 1;
+                    self.next_addr = 0;
+                }
+            }
+        }
+    }
+}
+
+/// Splits the huge frame into base frames.
+///
+/// If the caller holds the only reference to the frame, the frame is split in place. Otherwise,
+/// the contents are copied to new base frames, which are allocated according to the memory
+/// `policy`. If the allocation fails, the huge frame is returned with the error.
+pub(super) fn split_huge_frame(
+    frame: UFrame,
+    policy: Option<&MemoryPolicy>,
+) -> core::result::Result<Vec<UFrame>, (UFrame, Error)> {
+    debug_assert_eq!(frame.level(), HUGE_PAGE_LEVEL);
+
+    let frame = match Frame::<ChargedFrameMeta>::try_from(Frame::<dyn AnyFrameMeta>::from(frame)) {
+        Ok(frame) => match frame.split(ChargedFrameMeta::split_base_page) {
+            Ok(segment) => return Ok(segment.map(UFrame::from).collect()),
+            Err(frame) => UFrame::from(frame),
+        },
+        Err(frame) => UFrame::try_from(frame).unwrap(),
+    };
+
+    let mut base_frames = Vec::with_capacity(HUGE_PAGE_SIZE / PAGE_SIZE);
+    for offset in (0..HUGE_PAGE_SIZE).step_by(PAGE_SIZE) {
+        let base_frame = match alloc_user_frame(FrameAllocOptions::new().zeroed(false), policy) {
+            Ok(base_frame) => base_frame,
+            Err(err) => return Err((frame, err)),
+        };
+        base_frame
+            .writer()
+            .write(&mut frame.reader().skip(offset).limit(PAGE_SIZE));
+        base_frames.push(base_frame.into());
+    }
+
+    Ok(base_frames)
+}
@@ -0,0 +1,222 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! Userfaultfd contexts.
+//!
+//! A userfaultfd context allows the user space to handle the page faults in the registered
+//! ranges. The faulting thread is blocked until a handler thread resolves the fault (e.g., by
+//! populating the page with `UFFDIO_COPY`) and wakes the faulting thread up.
+//!
+//! Reference: <https://elixir.bootlin.com/linux/v6.0.9/source/fs/userfaultfd.c>
+
+use core::ops::Range;
+
+use align_ext::AlignExt;
+use ostd::{mm::PageFlags, sync::WaitQueue};
+
+use crate::{
+    events::IoEvents,
+    prelude::*,
+    process::{
+        posix_thread::AsPosixThread,
+        signal::{PollHandle, Pollable, Pollee},
+    },
+};
+
+/// The page flag that marks a page as write-protected by a userfaultfd.
+pub(super) const PAGE_FLAG_UFFD_WP: PageFlags = PageFlags::AVAIL1;
+
+bitflags! {
+    /// The kinds of page faults that are handled by a userfaultfd.
+    pub struct UserfaultMode: u64 {
+        /// Accesses to pages that are not mapped.
+        const MISSING = 1 << 0;
+        /// Writes to pages that are write-protected.
+        const WP      = 1 << 1;
+    }
+}
+
+bitflags! {
+    /// The flags that describe a page fault reported by a userfaultfd.
+    pub struct UserfaultFlags: u64 {
+        /// The page fault is caused by a write access.
+        const WRITE = 1 << 0;
+        /// The page fault is caused by a write to a write-protected page.
+        const WP    = 1 << 1;
+    }
+}
+
+/// A userfaultfd context.
+pub struct Userfaultfd {
+    inner: Mutex<Inner>,
+    pollee: Pollee,
+    wait_queue: WaitQueue,
+}
+
+struct Inner {
+    faults: VecDeque<PendingFault>,
+    next_id: u64,
+    is_released: bool,
+}
+
+/// A page fault that waits to be resolved by the user space.
+struct PendingFault {
+    id: u64,
+    fault: Userfault,
+    is_read: bool,
+}
+
+/// A page fault that is reported to the user space.
+#[derive(Debug, Clone, Copy)]
+pub struct Userfault {
+    /// The page-aligned address of the page fault.
+    pub address: Vaddr,
+    pub flags: UserfaultFlags,
+    /// The ID of the faulting thread.
+    pub tid: u32,
+}
+
+impl Userfaultfd {
+    pub fn new() -> Arc<Self> {
+        Arc::new(Self {
+            inner: Mutex::new(Inner {
+                faults: VecDeque::new(),
+                next_id: 0,
+                is_released: false,
+            }),
+            pollee: Pollee::new(),
+            wait_queue: WaitQueue::new(),
+        })
+    }
+
+    /// Reports the page fault at `address` and waits until it is resolved.
+    ///
+    /// The faulting access should be retried when this method returns `Ok`, whether or not
+    /// the page has been populated.
+    ///
+    /// # Errors
+    ///
+    /// This method returns an error with [`EINTR`] if a signal interrupts the waiting.
+    ///
+    /// [`EINTR`]: Errno::EINTR
+    pub(super) fn handle_fault(&self, address: Vaddr, flags: UserfaultFlags) -> Result<()> {
+        let tid = current_thread!().as_posix_thread().unwrap().tid();
+
+        let id = {
+            let mut inner = self.inner.lock();
+            if inner.is_released {
+                return Ok(());
+            }
+
+            let id = inner.next_id;
+            inner.next_id += 1;
+            inner.faults.push_back(PendingFault {
+                id,
+                fault: Userfault {
+                    address: address.align_down(PAGE_SIZE),
+                    flags,
+                    tid,
+                },
+                is_read: false,
+            });
+            id
+        };
+        self.pollee.notify(IoEvents::IN);
+
+        let is_resolved = || {
+            let inner = self.inner.lock();
+            (inner.is_released || inner.faults.iter().all(|fault| fault.id != id)).then_some(())
+        };
+        self.wait_queue.pause_until(is_resolved).inspect_err(|_| {
+            self.inner.lock().faults.retain(|fault| fault.id != id);
+            self.pollee.invalidate();
+        })
+    }
+
+    /// Takes the oldest page fault that has not been read by the user space.
+    ///
+    /// The page fault is still pending until it is woken up by [`Self::wake`].
+    pub fn take_unread_fault(&self) -> Option<Userfault> {
+        let mut inner = self.inner.lock();
+        let pending = inner.faults.iter_mut().find(|fault| !fault.is_read)?;
+        pending.is_read = true;
+        let fault = pending.fault;
+        drop(inner);
+
+        self.pollee.invalidate();
+        Some(fault)
+    }
+
+    /// Wakes up the threads that are waiting for the page faults within `range`.
+    pub fn wake(&self, range: &Range<Vaddr>) {
+        self.inner
+            .lock()
+            .faults
+            .retain(|fault| !range.contains(&fault.fault.address));
+        self.pollee.invalidate();
+        self.wait_queue.wake_all();
+    }
+
+    /// Releases the context when its file is closed.
+    ///
+    /// The pending page faults are woken up, and the following page faults in the registered
+    /// ranges are handled as if they are not registered.
+    pub fn release(&self) {
+        let mut inner = self.inner.lock();
+        inner.is_released = true;
+        inner.faults.clear();
+        drop(inner);
+
+        self.pollee.invalidate();
+        self.wait_queue.wake_all();
+    }
+
+    /// Returns whether the context has been released.
+    pub(super) fn is_released(&self) -> bool {
+        self.inner.lock().is_released
+    }
+
+    fn check_io_events(&self) -> IoEvents {
+        let inner = self.inner.lock();
+        if inner.faults.iter().any(|fault| !fault.is_read) {
+            IoEvents::IN
+        } else {
+            IoEvents::empty()
+        }
+    }
+}
+
+impl Pollable for Userfaultfd {
+    fn poll(&self, mask: IoEvents, poller: Option<&mut PollHandle>) -> IoEvents {
+        self.pollee
+            .poll_with(mask, poller, || self.check_io_events())
+    }
+}
+
+impl Debug for Userfaultfd {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("Userfaultfd")
+            .field("is_released", &self.is_released())
+            .finish_non_exhaustive()
+    }
+}
+
+/// The registration of a mapping to a userfaultfd.
+#[derive(Debug, Clone)]
+pub(super) struct UserfaultfdRegistration {
+    userfaultfd: Arc<Userfaultfd>,
+    mode: UserfaultMode,
+}
+
+impl UserfaultfdRegistration {
+    pub(super) fn new(userfaultfd: Arc<Userfaultfd>, mode: UserfaultMode) -> Self {
+        Self { userfaultfd, mode }
+    }
+
+    pub(super) fn userfaultfd(&self) -> &Arc<Userfaultfd> {
+        &self.userfaultfd
+    }
+
+    pub(super) fn mode(&self) -> UserfaultMode {
+        self.mode
+    }
+}
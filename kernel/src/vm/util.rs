@@ -1,14 +1,52 @@
 // SPDX-License-Identifier: MPL-2.0
 
 use ostd::mm::{Frame, FrameAllocOptions, UFrame, UntypedMem};
+use spin::Once;
 
+use super::{
+    memcg::{alloc_user_frame, alloc_user_huge_frame, ChargedFrameMeta},
+    mempolicy::MemoryPolicy,
+    thp::HUGE_PAGE_LEVEL,
+};
 use crate::prelude::*;
 
-/// Creates a new `Frame<()>` and initializes it with the contents of the `src`.
+/// Creates a new frame for the user memory and initializes it with the contents of the `src`.
 ///
-/// Note that it only duplicates the contents not the metadata.
-pub fn duplicate_frame(src: &UFrame) -> Result<Frame<()>> {
-    let new_frame = FrameAllocOptions::new().zeroed(false).alloc_frame()?;
+/// Note that it only duplicates the contents not the metadata. The new frame is charged to the
+/// memory group of the current process, and is allocated according to the memory `policy` (see
+/// [`alloc_user_frame`]). If the `src` is a huge frame, so is the new frame.
+pub fn duplicate_frame(
+    src: &UFrame,
+    policy: Option<&MemoryPolicy>,
+) -> Result<Frame<ChargedFrameMeta>> {
+    let mut options = FrameAllocOptions::new();
+    options.zeroed(false);
+    let new_frame = if src.level() == 1 {
+        alloc_user_frame(&options, policy)?
+    } else {
+        debug_assert_eq!(src.level(), HUGE_PAGE_LEVEL);
+        alloc_user_huge_frame(&options, policy)?
+    };
     new_frame.writer().write(&mut src.reader());
     Ok(new_frame)
 }
+
+static ZERO_FRAME: Once<UFrame> = Once::new();
+
+/// Returns the frame filled with zeros that is shared by all read-only anonymous pages.
+///
+/// The frame must never be written. It is mapped read-only, and a write access to it triggers
+/// a copy-on-write page fault. It is not charged to any memory group.
+pub fn zero_frame() -> Result<UFrame> {
+    let frame = ZERO_FRAME.try_call_once(|| -> Result<UFrame> {
+        Ok(FrameAllocOptions::new().alloc_frame()?.into())
+    })?;
+    Ok(frame.clone())
+}
+
+/// Returns whether the frame is the one returned by [`zero_frame`].
+pub fn is_zero_frame(frame: &UFrame) -> bool {
+    ZERO_FRAME
+        .get()
+        .is_some_and(|zero_frame| zero_frame.start_paddr() == frame.start_paddr())
+}
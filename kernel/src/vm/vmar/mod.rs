@@ -13,7 +13,10 @@ use align_ext::AlignExt;
 use aster_rights::Rights;
 use ostd::{
     cpu::CpuExceptionInfo,
-    mm::{tlb::TlbFlushOp, PageFlags, PageProperty, VmSpace, MAX_USERSPACE_VADDR},
+    mm::{
+        tlb::TlbFlushOp, vm_space::VmItem, Paddr, PageFlags, PageProperty, UFrame, VmSpace,
+        MAX_USERSPACE_VADDR,
+    },
 };
 
 use self::{
@@ -25,7 +28,12 @@ use crate::{
     prelude::*,
     thread::exception::{handle_page_fault_from_vm_space, PageFaultInfo},
     vm::{
+        mempolicy::MemoryPolicy,
         perms::VmPerms,
+        swap::SwapArea,
+        thp::{HugePageAdvice, HUGE_PAGE_SIZE},
+        userfaultfd::{UserfaultMode, Userfaultfd, UserfaultfdRegistration},
+        util::is_zero_frame,
         vmo::{Vmo, VmoRightsOp},
     },
 };
@@ -104,6 +112,380 @@ impl<R> Vmar<R> {
     pub fn resize_mapping(&self, map_addr: Vaddr, old_size: usize, new_size: usize) -> Result<()> {
         self.0.resize_mapping(map_addr, old_size, new_size)
     }
+
+    /// Remaps the pages in `old_range` to a range of `new_size` bytes.
+    ///
+    /// The old range must be within one mapping. If `new_addr` is specified, the pages are
+    /// moved to `new_addr` and the existing mappings there are unmapped. Otherwise, the
+    /// mapping is resized in place if possible, or moved to a free region if `may_move` is
+    /// true.
+    ///
+    /// On success, the start address of the remapped range is returned.
+    pub fn remap(
+        &self,
+        old_range: Range<Vaddr>,
+        new_addr: Option<Vaddr>,
+        new_size: usize,
+        may_move: bool,
+    ) -> Result<Vaddr> {
+        self.0.remap(old_range, new_addr, new_size, may_move)
+    }
+
+    /// Returns the start address of the mapping that grows down and contains `addr`.
+    ///
+    /// If `addr` is not in such a mapping, this method returns `None`.
+    pub fn grows_down_start(&self, addr: Vaddr) -> Option<Vaddr> {
+        let inner = self.0.inner.read();
+        inner
+            .vm_mappings
+            .find_one(&addr)
+            .filter(|vm_mapping| vm_mapping.grows_down())
+            .map(|vm_mapping| vm_mapping.map_to_addr())
+    }
+
+    /// Returns the VMO mapped at `addr` and the offset in the VMO that `addr` is mapped to.
+    ///
+    /// If `addr` is not in a mapping backed by a VMO, this method returns `None`.
+    pub fn vmo_at(&self, addr: Vaddr) -> Option<(Vmo, usize)> {
+        let inner = self.0.inner.read();
+        let vm_mapping = inner.vm_mappings.find_one(&addr)?;
+        let (vmo, offset) = vm_mapping.mapped_vmo()?;
+        let vmo = vmo.dup().ok()?;
+        Some((vmo, offset + (addr - vm_mapping.map_to_addr())))
+    }
+
+    /// Reads or writes the memory at `addr` on behalf of another process (e.g., a tracer).
+    ///
+    /// Unlike the accesses from the user space, the memory can be written even if it is
+    /// mapped as read-only and private. In that case, the pages are copied before they are
+    /// written, so the writes are not visible to other processes or the underlying files.
+    pub fn access_remote(&self, addr: Vaddr, buf: &mut [u8], is_write: bool) -> Result<()> {
+        self.0.access_remote(addr, buf, is_write)
+    }
+
+    /// Returns the ranges and the permissions of all the mappings in ascending order.
+    pub fn mappings(&self) -> Vec<(Range<Vaddr>, VmPerms)> {
+        self.0.mappings()
+    }
+
+    /// Returns the statistics of all the mappings in ascending order.
+    pub fn mapping_stats(&self) -> Vec<MappingStat> {
+        self.0.mapping_stats()
+    }
+
+    /// Returns the addresses and the information of the pages mapped within `range` in
+    /// ascending order.
+    pub fn query_pages(&self, range: Range<Vaddr>) -> Vec<(Vaddr, MappedPageInfo)> {
+        self.0.query_pages(range)
+    }
+
+    /// Returns the total size of the mappings within `range` in bytes.
+    pub fn mapped_size(&self, range: Range<Vaddr>) -> usize {
+        self.0.mapped_size(range)
+    }
+
+    /// Returns the total size of the pages that are resident in the memory in bytes.
+    ///
+    /// The pages that are mapped to the zero frame are not counted.
+    pub fn resident_size(&self) -> usize {
+        self.0.resident_size()
+    }
+
+    /// Writes the dirty pages of the shared mappings within `range` back to the underlying
+    /// files.
+    pub fn sync(&self, range: Range<Vaddr>) -> Result<()> {
+        self.0.sync(range)
+    }
+
+    /// Reads the page at `page_addr` when dumping the memory (e.g., into a core file).
+    ///
+    /// Unlike [`Self::access_remote`], the pages of anonymous mappings that have never been
+    /// accessed are read as zeros without being committed.
+    pub fn read_page_for_dump(&self, page_addr: Vaddr, buf: &mut [u8]) -> Result<()> {
+        self.0.read_page_for_dump(page_addr, buf)
+    }
+
+    /// Discards the pages mapped within `range`.
+    ///
+    /// The mappings are kept, but the following accesses will see the contents of the
+    /// underlying VMOs, or zeros for private anonymous mappings. If `range` contains unmapped
+    /// pages, the mapped pages are still discarded before an error is returned.
+    pub fn discard_pages(&self, range: Range<Vaddr>) -> Result<()> {
+        self.0.discard_pages(range, false)
+    }
+
+    /// Frees the pages mapped within `range`, which must be in private anonymous mappings.
+    ///
+    /// The following accesses will see either the original contents or zeros.
+    ///
+    /// TODO: Free the pages lazily under memory pressure, so that the pages can be reused if
+    /// they are written before being freed. Currently, they are discarded immediately.
+    pub fn free_pages(&self, range: Range<Vaddr>) -> Result<()> {
+        self.0.discard_pages(range, true)
+    }
+
+    /// Reads the pages of the VMO-backed mappings within `range` in advance.
+    ///
+    /// Like [`Self::discard_pages`], an error is returned if `range` contains unmapped pages.
+    pub fn read_ahead(&self, range: Range<Vaddr>) -> Result<()> {
+        self.0.read_ahead(range)
+    }
+
+    /// Registers the mappings within `range` to `userfaultfd`, which will handle the page
+    /// faults of the kinds in `mode`.
+    ///
+    /// Only private anonymous mappings can be registered. A mapping cannot be registered to
+    /// more than one userfaultfd at the same time.
+    pub fn register_userfaultfd(
+        &self,
+        range: Range<Vaddr>,
+        userfaultfd: &Arc<Userfaultfd>,
+        mode: UserfaultMode,
+    ) -> Result<()> {
+        self.0.register_userfaultfd(range, userfaultfd, mode)
+    }
+
+    /// Unregisters the mappings within `range` from `userfaultfd`.
+    ///
+    /// The pages that are write-protected for the userfaultfd are no longer protected.
+    pub fn unregister_userfaultfd(
+        &self,
+        range: Range<Vaddr>,
+        userfaultfd: &Arc<Userfaultfd>,
+    ) -> Result<()> {
+        self.0.unregister_userfaultfd(range, userfaultfd)
+    }
+
+    /// Write-protects the mapped pages within `range` for `userfaultfd`, or removes the
+    /// protection.
+    ///
+    /// The mappings within `range` must be registered to `userfaultfd` in the write-protect
+    /// mode.
+    pub fn write_protect_userfault(
+        &self,
+        range: Range<Vaddr>,
+        userfaultfd: &Arc<Userfaultfd>,
+        write_protect: bool,
+    ) -> Result<()> {
+        self.0
+            .write_protect_userfault(range, userfaultfd, write_protect)
+    }
+
+    /// Maps `frame` to the unmapped page at `page_addr` on behalf of `userfaultfd`.
+    ///
+    /// The page must be in a mapping registered to `userfaultfd`. If `write_protect` is true,
+    /// the page is write-protected for `userfaultfd`.
+    pub fn fill_userfault_page(
+        &self,
+        page_addr: Vaddr,
+        userfaultfd: &Arc<Userfaultfd>,
+        frame: UFrame,
+        write_protect: bool,
+    ) -> Result<()> {
+        self.0
+            .fill_userfault_page(page_addr, userfaultfd, frame, write_protect)
+    }
+
+    /// Sets the NUMA memory policy of the mappings within `range`.
+    ///
+    /// If `memory_policy` is `None`, the mappings use the memory policy of the faulting
+    /// threads. The policy only applies to the pages that are allocated later.
+    pub fn set_memory_policy(
+        &self,
+        range: Range<Vaddr>,
+        memory_policy: Option<Arc<MemoryPolicy>>,
+    ) -> Result<()> {
+        self.0.set_memory_policy(range, memory_policy)
+    }
+
+    /// Returns the NUMA memory policy of the mapping at `addr`.
+    pub fn memory_policy(&self, addr: Vaddr) -> Result<Option<Arc<MemoryPolicy>>> {
+        self.0.memory_policy(addr)
+    }
+
+    /// Returns the NUMA node of the page at `addr`.
+    ///
+    /// The page is committed for reading if it has not been committed.
+    pub fn node_of_page(&self, addr: Vaddr) -> Result<usize> {
+        self.0.node_of_page(addr)
+    }
+
+    /// Sets whether the pages of the mappings within `range` can be merged by KSM.
+    ///
+    /// Like [`Self::discard_pages`], an error is returned if `range` contains unmapped pages.
+    pub fn set_mergeable(&self, range: Range<Vaddr>, is_mergeable: bool) -> Result<()> {
+        self.0.set_mergeable(range, is_mergeable)
+    }
+
+    /// Sets the advice on whether the mappings within `range` should be backed by huge pages.
+    ///
+    /// Like [`Self::discard_pages`], an error is returned if `range` contains unmapped pages.
+    pub fn set_huge_page_advice(&self, range: Range<Vaddr>, advice: HugePageAdvice) -> Result<()> {
+        self.0.set_huge_page_advice(range, advice)
+    }
+
+    /// Collapses the base pages in the huge-page-aligned blocks of the eligible mappings into
+    /// huge pages, starting from `start_addr`, until at most `max_pages` pages are scanned.
+    ///
+    /// Returns the number of scanned pages and the address where the next scan should start, or
+    /// `None` if there are no more blocks to scan.
+    pub(in crate::vm) fn collapse_huge_pages(
+        &self,
+        start_addr: Vaddr,
+        max_pages: usize,
+    ) -> (usize, Option<Vaddr>) {
+        self.0.collapse_huge_pages(start_addr, max_pages)
+    }
+
+    /// Returns the addresses of at most `max_pages` mapped pages that can be merged by KSM,
+    /// starting from `start_addr`.
+    ///
+    /// The second returned value is the address where the next search should start, or `None`
+    /// if there are no more such pages.
+    pub(in crate::vm) fn mergeable_pages(
+        &self,
+        start_addr: Vaddr,
+        max_pages: usize,
+    ) -> (Vec<Vaddr>, Option<Vaddr>) {
+        self.0.mergeable_pages(start_addr, max_pages)
+    }
+
+    /// Replaces the mergeable page at `page_addr` with the frame returned by `replace`.
+    ///
+    /// The new frame is mapped read-only and is copied when it is written.
+    pub(in crate::vm) fn replace_mergeable_page(
+        &self,
+        page_addr: Vaddr,
+        replace: impl FnOnce(&UFrame) -> Option<UFrame>,
+    ) -> Result<()> {
+        self.0.replace_mergeable_page(page_addr, replace)
+    }
+
+    /// Replaces the pages mapped within `range` that satisfy `is_merged_page` with private
+    /// copies.
+    pub(in crate::vm) fn unmerge_pages(
+        &self,
+        range: Range<Vaddr>,
+        is_merged_page: impl Fn(&UFrame) -> bool,
+    ) -> Result<()> {
+        self.0.unmerge_pages(range, is_merged_page)
+    }
+
+    /// Swaps out the pages mapped within `range`, regardless of whether they are recently used.
+    ///
+    /// Only the pages of private anonymous mappings are swapped out, and the pages that are also
+    /// mapped elsewhere are kept. Nothing is swapped out if there are no active swap areas. Like
+    /// [`Self::discard_pages`], an error is returned if `range` contains unmapped pages.
+    pub fn page_out(&self, range: Range<Vaddr>) -> Result<()> {
+        self.0.page_out(range)
+    }
+
+    /// Returns the addresses of at most `max_pages` mapped pages that can be swapped out,
+    /// starting from `start_addr`.
+    ///
+    /// The second returned value is the address where the next search should start, or `None`
+    /// if there are no more such pages.
+    pub(in crate::vm) fn swappable_pages(
+        &self,
+        start_addr: Vaddr,
+        max_pages: usize,
+    ) -> (Vec<Vaddr>, Option<Vaddr>) {
+        self.0.swappable_pages(start_addr, max_pages)
+    }
+
+    /// Swaps out the page mapped at `page_addr` if it has not been accessed recently.
+    ///
+    /// Returns whether the page is swapped out.
+    pub(in crate::vm) fn swap_out_page(&self, page_addr: Vaddr) -> Result<bool> {
+        self.0.swap_out_page(page_addr)
+    }
+
+    /// Swaps in all the pages that are stored in `area`.
+    pub(in crate::vm) fn swap_in_area(&self, area: &Arc<SwapArea>) -> Result<()> {
+        self.0.swap_in_area(area)
+    }
+
+    /// Locks or unlocks the pages of the mappings within `range` in the memory.
+    ///
+    /// If `range` contains unmapped pages, an error is returned and nothing is changed. The
+    /// pages are not committed by this method; use [`Self::populate`] to commit them.
+    pub fn set_locked(&self, range: Range<Vaddr>, is_locked: bool) -> Result<()> {
+        self.0.set_locked(range, is_locked)
+    }
+
+    /// Locks or unlocks the pages of all the mappings in the memory.
+    pub fn set_all_locked(&self, is_locked: bool) {
+        self.0.set_all_locked(is_locked);
+    }
+
+    /// Returns the total size of the locked mappings within `range` in bytes.
+    pub fn locked_size(&self, range: Range<Vaddr>) -> usize {
+        self.0.locked_size(range)
+    }
+
+    /// Returns how the mappings that are created in the future are locked.
+    pub fn future_lock(&self) -> Option<LockMode> {
+        self.0.inner.read().future_lock
+    }
+
+    /// Sets how the mappings that are created in the future are locked.
+    ///
+    /// If `future_lock` is `None`, the new mappings are not locked.
+    pub fn set_future_lock(&self, future_lock: Option<LockMode>) {
+        self.0.inner.write().future_lock = future_lock;
+    }
+
+    /// Returns the lowest address where the mappings without a fixed address are placed.
+    pub fn mmap_base(&self) -> Vaddr {
+        self.0.inner.read().mmap_base
+    }
+
+    /// Sets the lowest address where the mappings without a fixed address are placed.
+    ///
+    /// If there is no free region above `mmap_base`, the mappings are placed below it.
+    pub fn set_mmap_base(&self, mmap_base: Vaddr) {
+        debug_assert!(mmap_base % PAGE_SIZE == 0);
+        debug_assert!(is_userspace_vaddr(mmap_base));
+        self.0.inner.write().mmap_base = mmap_base;
+    }
+
+    /// Returns the highest address where the mappings without a fixed address are placed.
+    pub fn mmap_top(&self) -> Vaddr {
+        self.0.inner.read().mmap_top
+    }
+
+    /// Sets the highest address where the mappings without a fixed address are placed.
+    ///
+    /// This limits the mappings of the programs that can only access the lower part of the
+    /// address space, e.g., the 32-bit programs.
+    pub fn set_mmap_top(&self, mmap_top: Vaddr) {
+        debug_assert!(mmap_top % PAGE_SIZE == 0);
+        debug_assert!(mmap_top <= ROOT_VMAR_CAP_ADDR);
+        self.0.inner.write().mmap_top = mmap_top;
+    }
+
+    /// Commits and maps the pages within `range` as if they were accessed by the user space.
+    ///
+    /// The pages of inaccessible mappings and the unmapped pages are skipped.
+    pub fn populate(&self, range: Range<Vaddr>) -> Result<()> {
+        self.0.populate(range)
+    }
+
+    /// Commits the pages within `range` as if they were accessed by the user space, and returns
+    /// their frames in ascending order.
+    ///
+    /// If `is_write` is true, the pages are committed for writing, i.e., the private pages are
+    /// copied first. The returned frames stay valid even if the pages are unmapped later, so the
+    /// devices can access the user buffers directly (e.g., for direct I/O). While the frames are
+    /// held, the pages are neither merged by KSM nor swapped out, since both skip the pages with
+    /// extra references.
+    ///
+    /// # Errors
+    ///
+    /// This method fails with `EFAULT` if any page is not accessible.
+    pub fn pin_pages(&self, range: Range<Vaddr>, is_write: bool) -> Result<Vec<UFrame>> {
+        self.0.pin_pages(range, is_write)
+    }
 }
 
 pub(super) struct Vmar_ {
@@ -120,12 +502,88 @@ pub(super) struct Vmar_ {
 struct VmarInner {
     /// The mapped pages and associated metadata.
     vm_mappings: IntervalSet<Vaddr, VmMapping>,
+    /// How the mappings that are created in the future are locked (see `mlockall`).
+    future_lock: Option<LockMode>,
+    /// The lowest address where the mappings without a fixed address are placed, which is
+    /// randomized by the user address space layout randomization.
+    mmap_base: Vaddr,
+    /// The highest address where the mappings without a fixed address are placed.
+    mmap_top: Vaddr,
+}
+
+/// The statistics of a mapping and the pages mapped in it.
+///
+/// The sizes of the pages are in bytes. The pages that are mapped to the zero frame are not
+/// counted.
+#[derive(Debug, Clone)]
+pub struct MappingStat {
+    /// The range of the mapping.
+    pub range: Range<Vaddr>,
+    /// The permissions of the mapping.
+    pub perms: VmPerms,
+    /// Whether the mapping is shared.
+    pub is_shared: bool,
+    /// The offset in the mapped VMO, or `None` for private anonymous mappings.
+    pub vmo_offset: Option<usize>,
+    /// Whether the mapping grows down.
+    pub grows_down: bool,
+    /// Whether the mapping is locked in the memory.
+    pub is_locked: bool,
+    /// Whether the pages of the mapping can be merged by KSM.
+    pub is_mergeable: bool,
+    /// The advice on whether the mapping should be backed by huge pages.
+    pub huge_page_advice: HugePageAdvice,
+    /// The size of the resident pages.
+    pub rss: usize,
+    /// The proportional size of the resident pages, where each page is divided by the number
+    /// of its mappings.
+    pub pss: usize,
+    /// The size of the clean pages that are also mapped elsewhere.
+    pub shared_clean: usize,
+    /// The size of the dirty pages that are also mapped elsewhere.
+    pub shared_dirty: usize,
+    /// The size of the clean pages that are only mapped here.
+    pub private_clean: usize,
+    /// The size of the dirty pages that are only mapped here.
+    pub private_dirty: usize,
+    /// The size of the pages that have been accessed.
+    pub referenced: usize,
+    /// The size of the pages that do not belong to any file.
+    pub anonymous: usize,
+    /// The size of the swapped-out pages.
+    pub swap: usize,
+    /// The proportional size of the swapped-out pages, where each page is divided by the
+    /// number of the mappings that share it.
+    pub swap_pss: usize,
+}
+
+/// The information of a page mapped in a VMAR.
+#[derive(Debug, Clone, Copy)]
+pub struct MappedPageInfo {
+    /// The physical address of the page.
+    pub paddr: Paddr,
+    /// Whether the mapping of the page is backed by a VMO (i.e., a file or shared memory).
+    pub is_file_or_shared: bool,
+    /// Whether the page is mapped exclusively by this mapping.
+    pub is_exclusive: bool,
+}
+
+/// How the pages of locked mappings are committed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LockMode {
+    /// The pages are committed when they are locked.
+    Populate,
+    /// The pages are committed when they are accessed.
+    OnFault,
 }
 
 impl VmarInner {
     const fn new() -> Self {
         Self {
             vm_mappings: IntervalSet::new(),
+            future_lock: None,
+            mmap_base: ROOT_VMAR_LOWEST_ADDR,
+            mmap_top: ROOT_VMAR_CAP_ADDR,
         }
     }
 
@@ -139,7 +597,7 @@ impl VmarInner {
             .next()
             .is_some()
         {
-            return_errno_with_message!(Errno::EACCES, "Requested region is already occupied");
+            return_errno_with_message!(Errno::EEXIST, "Requested region is already occupied");
         }
 
         Ok(offset..(offset + size))
@@ -162,6 +620,9 @@ impl VmarInner {
         }
 
         for vm_mapping_addr in mappings_to_remove {
+            let vm_mapping = self.vm_mappings.find_one(&vm_mapping_addr).unwrap();
+            vm_mapping.split_huge_pages_across(vm_space, &range)?;
+
             let vm_mapping = self.vm_mappings.remove(&vm_mapping_addr).unwrap();
             let vm_mapping_range = vm_mapping.range();
             let intersected_range = get_intersected_range(&range, &vm_mapping_range);
@@ -182,6 +643,9 @@ impl VmarInner {
 
     /// Allocates a free region for mapping.
     ///
+    /// The region is searched above `mmap_base` first, and then above the lowest address. It
+    /// never goes beyond `mmap_top`.
+    ///
     /// If no such region is found, return an error.
     fn alloc_free_region(&mut self, size: usize, align: usize) -> Result<Range<Vaddr>> {
         // Fast path that there's still room to the end.
@@ -191,42 +655,148 @@ impl VmarInner {
             .next_back()
             .map_or(ROOT_VMAR_LOWEST_ADDR, |vm_mapping| vm_mapping.range().end);
         // FIXME: The up-align may overflow.
-        let last_occupied_aligned = highest_occupied.align_up(align);
+        let last_occupied_aligned = highest_occupied.max(self.mmap_base).align_up(align);
         if let Some(last) = last_occupied_aligned.checked_add(size) {
-            if last <= ROOT_VMAR_CAP_ADDR {
+            if last <= self.mmap_top {
                 return Ok(last_occupied_aligned..last);
             }
         }
 
         // Slow path that we need to search for a free region.
-        // Here, we use a simple brute-force FIRST-FIT algorithm.
-        // Allocate as low as possible to reduce fragmentation.
-        let mut last_end: Vaddr = ROOT_VMAR_LOWEST_ADDR;
+        if self.mmap_base > ROOT_VMAR_LOWEST_ADDR {
+            if let Some(region) = self.find_free_region(self.mmap_base, size, align) {
+                return Ok(region);
+            }
+        }
+        if let Some(region) = self.find_free_region(ROOT_VMAR_LOWEST_ADDR, size, align) {
+            return Ok(region);
+        }
+
+        return_errno_with_message!(Errno::ENOMEM, "Cannot find free region for mapping");
+    }
+
+    /// Finds a free region for mapping above `lowest_addr`.
+    ///
+    /// Here, we use a simple brute-force FIRST-FIT algorithm.
+    /// Allocate as low as possible to reduce fragmentation.
+    fn find_free_region(
+        &self,
+        lowest_addr: Vaddr,
+        size: usize,
+        align: usize,
+    ) -> Option<Range<Vaddr>> {
+        let mut last_end: Vaddr = lowest_addr;
         for vm_mapping in self.vm_mappings.iter() {
             let range = vm_mapping.range();
-
-            debug_assert!(range.start >= last_end);
-            debug_assert!(range.end <= highest_occupied);
+            if range.end <= lowest_addr {
+                continue;
+            }
 
             let last_aligned = last_end.align_up(align);
-            let needed_end = last_aligned
-                .checked_add(size)
-                .ok_or(Error::new(Errno::ENOMEM))?;
+            let needed_end = last_aligned.checked_add(size)?;
+            if needed_end > self.mmap_top {
+                return None;
+            }
 
-            if needed_end <= range.start {
-                return Ok(last_aligned..needed_end);
+            // Leave the guard gap below a mapping that grows down.
+            let gap_start = if vm_mapping.grows_down() {
+                range.start.saturating_sub(STACK_GUARD_GAP)
+            } else {
+                range.start
+            };
+            if needed_end <= gap_start {
+                return Some(last_aligned..needed_end);
             }
 
-            last_end = range.end;
+            last_end = last_end.max(range.end);
         }
 
-        return_errno_with_message!(Errno::ENOMEM, "Cannot find free region for mapping");
+        None
+    }
+
+    /// Grows the mapping above `addr` down to cover `addr`, if the mapping grows down.
+    ///
+    /// Like Linux, the growth fails if it leaves less than [`STACK_GUARD_GAP`] bytes between
+    /// the mapping and the accessible mapping below it.
+    ///
+    /// Returns whether the mapping has grown.
+    fn grow_down(&mut self, addr: Vaddr) -> bool {
+        let new_start = addr.align_down(PAGE_SIZE);
+        if new_start < ROOT_VMAR_LOWEST_ADDR {
+            return false;
+        }
+
+        let Some(vm_mapping) = self
+            .vm_mappings
+            .find(&(new_start..ROOT_VMAR_CAP_ADDR))
+            .next()
+        else {
+            return false;
+        };
+        if !vm_mapping.grows_down() {
+            return false;
+        }
+        let vm_mapping_addr = vm_mapping.map_to_addr();
+
+        let gap_start = new_start.saturating_sub(STACK_GUARD_GAP);
+        if self
+            .vm_mappings
+            .find(&(gap_start..new_start))
+            .any(|prev| !prev.grows_down() && !prev.perms().is_empty())
+        {
+            return false;
+        }
+
+        let vm_mapping = self.vm_mappings.remove(&vm_mapping_addr).unwrap();
+        self.vm_mappings.insert(vm_mapping.grow_down(new_start));
+        true
+    }
+
+    /// Sets the userfaultfd registration of the parts within `range` of the mappings at
+    /// `vm_mapping_addrs`.
+    fn set_userfaultfd(
+        &mut self,
+        vm_space: &VmSpace,
+        range: &Range<Vaddr>,
+        vm_mapping_addrs: Vec<Vaddr>,
+        registration: Option<UserfaultfdRegistration>,
+    ) -> Result<()> {
+        for vm_mapping_addr in vm_mapping_addrs {
+            let vm_mapping = self.vm_mappings.find_one(&vm_mapping_addr).unwrap();
+            // The page faults of the registered pages are reported per base page.
+            if registration.is_some() {
+                vm_mapping.split_huge_pages(vm_space, range)?;
+            } else {
+                vm_mapping.split_huge_pages_across(vm_space, range)?;
+            }
+
+            let vm_mapping = self.vm_mappings.remove(&vm_mapping_addr).unwrap();
+            let intersected_range = get_intersected_range(range, &vm_mapping.range());
+
+            let (left, taken, right) = vm_mapping.split_range(&intersected_range)?;
+            self.vm_mappings
+                .insert(taken.set_userfaultfd(registration.clone()));
+
+            if let Some(left) = left {
+                self.vm_mappings.insert(left);
+            }
+            if let Some(right) = right {
+                self.vm_mappings.insert(right);
+            }
+        }
+
+        Ok(())
     }
 }
 
 pub const ROOT_VMAR_LOWEST_ADDR: Vaddr = 0x001_0000; // 64 KiB is the Linux configurable default
 const ROOT_VMAR_CAP_ADDR: Vaddr = MAX_USERSPACE_VADDR;
 
+/// The size of the gap that is kept below a mapping that grows down.
+///
+/// This is the default value of Linux.
+const STACK_GUARD_GAP: usize = 256 * PAGE_SIZE;
+
 /// Returns whether the input `vaddr` is a legal user space virtual address.
 pub fn is_userspace_vaddr(vaddr: Vaddr) -> bool {
     (ROOT_VMAR_LOWEST_ADDR..ROOT_VMAR_CAP_ADDR).contains(&vaddr)
@@ -249,9 +819,7 @@ impl Vmar_ {
     }
 
     fn new_root() -> Arc<Self> {
-        let vmar_inner = VmarInner {
-            vm_mappings: IntervalSet::new(),
-        };
+        let vmar_inner = VmarInner::new();
         let mut vm_space = VmSpace::new();
         vm_space.register_page_fault_handler(handle_page_fault_wrapper);
         Vmar_::new(vmar_inner, Arc::new(vm_space), 0, ROOT_VMAR_CAP_ADDR)
@@ -279,6 +847,9 @@ impl Vmar_ {
             if perms == vm_mapping_perms {
                 continue;
             }
+            let vm_mapping = inner.vm_mappings.find_one(&vm_mapping_addr).unwrap();
+            vm_mapping.split_huge_pages_across(vm_space, &range)?;
+
             let vm_mapping = inner.vm_mappings.remove(&vm_mapping_addr).unwrap();
             let vm_mapping_range = vm_mapping.range();
             let intersected_range = get_intersected_range(&range, &vm_mapping_range);
@@ -312,12 +883,689 @@ impl Vmar_ {
 
         if let Some(vm_mapping) = inner.vm_mappings.find_one(&address) {
             debug_assert!(vm_mapping.range().contains(&address));
+            if let Some((userfaultfd, flags)) =
+                vm_mapping.check_userfault(&self.vm_space, page_fault_info)
+            {
+                // The lock must be released so that the handler can resolve the page fault.
+                drop(inner);
+                return userfaultfd.handle_fault(address, flags);
+            }
+            return vm_mapping.handle_page_fault(&self.vm_space, page_fault_info);
+        }
+        drop(inner);
+
+        // The address may be below a mapping that grows down (e.g., a stack).
+        let mut inner = self.inner.write();
+        if inner.vm_mappings.find_one(&address).is_none() {
+            inner.grow_down(address);
+        }
+        if let Some(vm_mapping) = inner.vm_mappings.find_one(&address) {
             return vm_mapping.handle_page_fault(&self.vm_space, page_fault_info);
         }
 
         return_errno_with_message!(Errno::EACCES, "page fault addr is not in current vmar");
     }
 
+    pub fn access_remote(&self, addr: Vaddr, buf: &mut [u8], is_write: bool) -> Result<()> {
+        let inner = self.inner.read();
+
+        let mut offset = 0;
+        while offset < buf.len() {
+            let Some(cur_addr) = addr.checked_add(offset) else {
+                return_errno_with_message!(Errno::EIO, "the address overflows");
+            };
+            let Some(vm_mapping) = inner.vm_mappings.find_one(&cur_addr) else {
+                return_errno_with_message!(Errno::EIO, "the address is not mapped");
+            };
+
+            // Access at most one page each time.
+            let len = (buf.len() - offset).min(PAGE_SIZE - cur_addr % PAGE_SIZE);
+            vm_mapping.access_remote(
+                &self.vm_space,
+                cur_addr,
+                &mut buf[offset..offset + len],
+                is_write,
+            )?;
+            offset += len;
+        }
+
+        Ok(())
+    }
+
+    fn mappings(&self) -> Vec<(Range<Vaddr>, VmPerms)> {
+        let inner = self.inner.read();
+        inner
+            .vm_mappings
+            .iter()
+            .map(|vm_mapping| (vm_mapping.range(), vm_mapping.perms()))
+            .collect()
+    }
+
+    fn mapping_stats(&self) -> Vec<MappingStat> {
+        let inner = self.inner.read();
+        inner
+            .vm_mappings
+            .iter()
+            .filter_map(|vm_mapping| vm_mapping.stat(&self.vm_space).ok())
+            .collect()
+    }
+
+    fn query_pages(&self, range: Range<Vaddr>) -> Vec<(Vaddr, MappedPageInfo)> {
+        let inner = self.inner.read();
+        inner
+            .vm_mappings
+            .find(&range)
+            .filter_map(|vm_mapping| vm_mapping.query_pages(&self.vm_space, &range).ok())
+            .flatten()
+            .collect()
+    }
+
+    fn mapped_size(&self, range: Range<Vaddr>) -> usize {
+        let inner = self.inner.read();
+        inner
+            .vm_mappings
+            .find(&range)
+            .map(|vm_mapping| {
+                let mapping_range = vm_mapping.range();
+                let start = mapping_range.start.max(range.start);
+                let end = mapping_range.end.min(range.end);
+                end.saturating_sub(start)
+            })
+            .sum()
+    }
+
+    fn resident_size(&self) -> usize {
+        let inner = self.inner.read();
+        let mut size = 0;
+        for vm_mapping in inner.vm_mappings.iter() {
+            let Ok(cursor) = self.vm_space.cursor(&vm_mapping.range()) else {
+                continue;
+            };
+            size += cursor
+                .filter_map(|item| match item {
+                    VmItem::Mapped { frame, .. } if !is_zero_frame(&frame) => Some(frame.size()),
+                    _ => None,
+                })
+                .sum::<usize>();
+        }
+        size
+    }
+
+    fn sync(&self, range: Range<Vaddr>) -> Result<()> {
+        let inner = self.inner.read();
+        for vm_mapping in inner.vm_mappings.find(&range) {
+            vm_mapping.sync(&self.vm_space, &range)?;
+        }
+        Ok(())
+    }
+
+    fn read_page_for_dump(&self, page_addr: Vaddr, buf: &mut [u8]) -> Result<()> {
+        debug_assert!(page_addr % PAGE_SIZE == 0 && buf.len() == PAGE_SIZE);
+
+        let inner = self.inner.read();
+        let Some(vm_mapping) = inner.vm_mappings.find_one(&page_addr) else {
+            return_errno_with_message!(Errno::EIO, "the address is not mapped");
+        };
+        vm_mapping.read_page_for_dump(&self.vm_space, page_addr, buf)
+    }
+
+    fn discard_pages(&self, range: Range<Vaddr>, private_anonymous_only: bool) -> Result<()> {
+        let inner = self.inner.read();
+
+        if private_anonymous_only
+            && inner
+                .vm_mappings
+                .find(&range)
+                .any(|vm_mapping| !vm_mapping.is_private_anonymous())
+        {
+            return_errno_with_message!(
+                Errno::EINVAL,
+                "only the pages of private anonymous mappings can be freed"
+            );
+        }
+
+        if inner
+            .vm_mappings
+            .find(&range)
+            .any(|vm_mapping| vm_mapping.is_locked())
+        {
+            return_errno_with_message!(
+                Errno::EINVAL,
+                "the pages of locked mappings cannot be discarded"
+            );
+        }
+
+        for vm_mapping in inner.vm_mappings.find(&range) {
+            vm_mapping.discard_pages(&self.vm_space, &range)?;
+        }
+
+        check_fully_mapped(&inner, &range)
+    }
+
+    fn read_ahead(&self, range: Range<Vaddr>) -> Result<()> {
+        let inner = self.inner.read();
+
+        for vm_mapping in inner.vm_mappings.find(&range) {
+            vm_mapping.read_ahead(&range);
+        }
+
+        check_fully_mapped(&inner, &range)
+    }
+
+    fn register_userfaultfd(
+        &self,
+        range: Range<Vaddr>,
+        userfaultfd: &Arc<Userfaultfd>,
+        mode: UserfaultMode,
+    ) -> Result<()> {
+        let mut inner = self.inner.write();
+
+        let mut vm_mapping_addrs = Vec::new();
+        for vm_mapping in inner.vm_mappings.find(&range) {
+            if !vm_mapping.is_private_anonymous() {
+                return_errno_with_message!(
+                    Errno::EINVAL,
+                    "only private anonymous mappings can be registered"
+                );
+            }
+            if vm_mapping.userfaultfd().is_some_and(|registration| {
+                !Arc::ptr_eq(registration.userfaultfd(), userfaultfd)
+                    && !registration.userfaultfd().is_released()
+            }) {
+                return_errno_with_message!(
+                    Errno::EBUSY,
+                    "the mapping is registered to another userfaultfd"
+                );
+            }
+            vm_mapping_addrs.push(vm_mapping.map_to_addr());
+        }
+        if vm_mapping_addrs.is_empty() {
+            return_errno_with_message!(Errno::EINVAL, "the range is not mapped");
+        }
+
+        let registration = UserfaultfdRegistration::new(userfaultfd.clone(), mode);
+        inner.set_userfaultfd(&self.vm_space, &range, vm_mapping_addrs, Some(registration))
+    }
+
+    fn set_memory_policy(
+        &self,
+        range: Range<Vaddr>,
+        memory_policy: Option<Arc<MemoryPolicy>>,
+    ) -> Result<()> {
+        let mut inner = self.inner.write();
+
+        // Unlike `madvise`, `mbind` fails with `EFAULT` if the range contains unmapped pages.
+        if check_fully_mapped(&inner, &range).is_err() {
+            return_errno_with_message!(Errno::EFAULT, "the range contains unmapped pages");
+        }
+
+        let vm_mapping_addrs: Vec<Vaddr> = inner
+            .vm_mappings
+            .find(&range)
+            .map(|vm_mapping| vm_mapping.map_to_addr())
+            .collect();
+        for vm_mapping_addr in vm_mapping_addrs {
+            let vm_mapping = inner.vm_mappings.find_one(&vm_mapping_addr).unwrap();
+            vm_mapping.split_huge_pages_across(&self.vm_space, &range)?;
+
+            let vm_mapping = inner.vm_mappings.remove(&vm_mapping_addr).unwrap();
+            let intersected_range = get_intersected_range(&range, &vm_mapping.range());
+
+            let (left, taken, right) = vm_mapping.split_range(&intersected_range)?;
+            inner
+                .vm_mappings
+                .insert(taken.set_memory_policy(memory_policy.clone()));
+
+            if let Some(left) = left {
+                inner.vm_mappings.insert(left);
+            }
+            if let Some(right) = right {
+                inner.vm_mappings.insert(right);
+            }
+        }
+
+        Ok(())
+    }
+
+    fn memory_policy(&self, addr: Vaddr) -> Result<Option<Arc<MemoryPolicy>>> {
+        let inner = self.inner.read();
+        let Some(vm_mapping) = inner.vm_mappings.find_one(&addr) else {
+            return_errno_with_message!(Errno::EFAULT, "the address is not mapped");
+        };
+        Ok(vm_mapping.memory_policy().cloned())
+    }
+
+    fn node_of_page(&self, addr: Vaddr) -> Result<usize> {
+        let inner = self.inner.read();
+        let Some(vm_mapping) = inner.vm_mappings.find_one(&addr) else {
+            return_errno_with_message!(Errno::EFAULT, "the address is not mapped");
+        };
+        vm_mapping.node_of_page(&self.vm_space, addr)
+    }
+
+    fn set_mergeable(&self, range: Range<Vaddr>, is_mergeable: bool) -> Result<()> {
+        let mut inner = self.inner.write();
+
+        let vm_mapping_addrs: Vec<Vaddr> = inner
+            .vm_mappings
+            .find(&range)
+            .map(|vm_mapping| vm_mapping.map_to_addr())
+            .collect();
+        for vm_mapping_addr in vm_mapping_addrs {
+            let vm_mapping = inner.vm_mappings.find_one(&vm_mapping_addr).unwrap();
+            // The pages are merged per base page.
+            if is_mergeable {
+                vm_mapping.split_huge_pages(&self.vm_space, &range)?;
+            } else {
+                vm_mapping.split_huge_pages_across(&self.vm_space, &range)?;
+            }
+
+            let vm_mapping = inner.vm_mappings.remove(&vm_mapping_addr).unwrap();
+            let intersected_range = get_intersected_range(&range, &vm_mapping.range());
+
+            let (left, taken, right) = vm_mapping.split_range(&intersected_range)?;
+            inner.vm_mappings.insert(taken.set_mergeable(is_mergeable));
+
+            if let Some(left) = left {
+                inner.vm_mappings.insert(left);
+            }
+            if let Some(right) = right {
+                inner.vm_mappings.insert(right);
+            }
+        }
+
+        check_fully_mapped(&inner, &range)
+    }
+
+    fn set_huge_page_advice(&self, range: Range<Vaddr>, advice: HugePageAdvice) -> Result<()> {
+        let mut inner = self.inner.write();
+
+        let vm_mapping_addrs: Vec<Vaddr> = inner
+            .vm_mappings
+            .find(&range)
+            .filter(|vm_mapping| vm_mapping.huge_page_advice() != advice)
+            .map(|vm_mapping| vm_mapping.map_to_addr())
+            .collect();
+        for vm_mapping_addr in vm_mapping_addrs {
+            let vm_mapping = inner.vm_mappings.find_one(&vm_mapping_addr).unwrap();
+            vm_mapping.split_huge_pages_across(&self.vm_space, &range)?;
+
+            let vm_mapping = inner.vm_mappings.remove(&vm_mapping_addr).unwrap();
+            let intersected_range = get_intersected_range(&range, &vm_mapping.range());
+
+            let (left, taken, right) = vm_mapping.split_range(&intersected_range)?;
+            inner.vm_mappings.insert(taken.set_huge_page_advice(advice));
+
+            if let Some(left) = left {
+                inner.vm_mappings.insert(left);
+            }
+            if let Some(right) = right {
+                inner.vm_mappings.insert(right);
+            }
+        }
+
+        check_fully_mapped(&inner, &range)
+    }
+
+    fn set_locked(&self, range: Range<Vaddr>, is_locked: bool) -> Result<()> {
+        let mut inner = self.inner.write();
+
+        check_fully_mapped(&inner, &range)?;
+
+        let vm_mapping_addrs: Vec<Vaddr> = inner
+            .vm_mappings
+            .find(&range)
+            .filter(|vm_mapping| vm_mapping.is_locked() != is_locked)
+            .map(|vm_mapping| vm_mapping.map_to_addr())
+            .collect();
+        for vm_mapping_addr in vm_mapping_addrs {
+            let vm_mapping = inner.vm_mappings.find_one(&vm_mapping_addr).unwrap();
+            vm_mapping.split_huge_pages_across(&self.vm_space, &range)?;
+
+            let vm_mapping = inner.vm_mappings.remove(&vm_mapping_addr).unwrap();
+            let intersected_range = get_intersected_range(&range, &vm_mapping.range());
+
+            let (left, taken, right) = vm_mapping.split_range(&intersected_range)?;
+            inner.vm_mappings.insert(taken.set_locked(is_locked));
+
+            if let Some(left) = left {
+                inner.vm_mappings.insert(left);
+            }
+            if let Some(right) = right {
+                inner.vm_mappings.insert(right);
+            }
+        }
+
+        Ok(())
+    }
+
+    fn set_all_locked(&self, is_locked: bool) {
+        let mut inner = self.inner.write();
+
+        let vm_mappings: Vec<VmMapping> = inner
+            .vm_mappings
+            .take(&(self.base..self.base + self.size))
+            .collect();
+        for vm_mapping in vm_mappings {
+            inner.vm_mappings.insert(vm_mapping.set_locked(is_locked));
+        }
+    }
+
+    fn locked_size(&self, range: Range<Vaddr>) -> usize {
+        let inner = self.inner.read();
+        inner
+            .vm_mappings
+            .find(&range)
+            .filter(|vm_mapping| vm_mapping.is_locked())
+            .map(|vm_mapping| get_intersected_range(&range, &vm_mapping.range()).len())
+            .sum()
+    }
+
+    fn populate(&self, range: Range<Vaddr>) -> Result<()> {
+        let inner = self.inner.read();
+        for vm_mapping in inner.vm_mappings.find(&range) {
+            vm_mapping.populate(&self.vm_space, &range)?;
+        }
+        Ok(())
+    }
+
+    fn pin_pages(&self, range: Range<Vaddr>, is_write: bool) -> Result<Vec<UFrame>> {
+        debug_assert!(range.start % PAGE_SIZE == 0 && range.end % PAGE_SIZE == 0);
+        let required_perms = if is_write {
+            VmPerms::READ | VmPerms::WRITE
+        } else {
+            VmPerms::READ
+        };
+
+        let mut frames = Vec::with_capacity(range.len() / PAGE_SIZE);
+        for address in range.step_by(PAGE_SIZE) {
+            let page_fault_info = PageFaultInfo {
+                address,
+                required_perms,
+            };
+            let frame = loop {
+                if self.handle_page_fault(&page_fault_info).is_err() {
+                    return_errno_with_message!(Errno::EFAULT, "the page is not accessible");
+                }
+
+                // The page may have been unmapped (e.g., swapped out) or write-protected (e.g.,
+                // to be merged by KSM) after the page fault is handled. If so, the page fault is
+                // handled again, so that the pinned page is the one that the user space sees.
+                let mut cursor = self.vm_space.cursor(&(address..address + PAGE_SIZE))?;
+                if let VmItem::Mapped { frame, prop, .. } = cursor.query()?
+                    && VmPerms::from(prop.flags).contains(required_perms)
+                {
+                    if frame.level() == 1 {
+                        break frame;
+                    }
+                    // The pinned pages are base pages, so the huge page is split first.
+                    drop(cursor);
+                    self.split_huge_page(address)?;
+                }
+            };
+            frames.push(frame);
+        }
+
+        Ok(frames)
+    }
+
+    fn split_huge_page(&self, addr: Vaddr) -> Result<()> {
+        let inner = self.inner.read();
+        let Some(vm_mapping) = inner.vm_mappings.find_one(&addr) else {
+            return Ok(());
+        };
+        vm_mapping.split_huge_page(&self.vm_space, addr)
+    }
+
+    fn collapse_huge_pages(&self, start_addr: Vaddr, max_pages: usize) -> (usize, Option<Vaddr>) {
+        // Hold the write lock so that no page faults are handled while the page tables are
+        // replaced.
+        let inner = self.inner.write();
+
+        let mut nr_scanned = 0;
+        for vm_mapping in inner
+            .vm_mappings
+            .find(&(start_addr..ROOT_VMAR_CAP_ADDR))
+            .filter(|vm_mapping| vm_mapping.is_huge_page_eligible())
+        {
+            let blocks_start = start_addr
+                .max(vm_mapping.map_to_addr())
+                .align_up(HUGE_PAGE_SIZE);
+            let blocks_end = vm_mapping.map_end().align_down(HUGE_PAGE_SIZE);
+
+            for block_start in (blocks_start..blocks_end).step_by(HUGE_PAGE_SIZE) {
+                if nr_scanned >= max_pages {
+                    return (nr_scanned, Some(block_start));
+                }
+                nr_scanned += HUGE_PAGE_SIZE / PAGE_SIZE;
+
+                let block = block_start..block_start + HUGE_PAGE_SIZE;
+                let _ = vm_mapping.collapse_huge_page(&self.vm_space, &block);
+            }
+        }
+
+        (nr_scanned, None)
+    }
+
+    fn mergeable_pages(&self, start_addr: Vaddr, max_pages: usize) -> (Vec<Vaddr>, Option<Vaddr>) {
+        let inner = self.inner.read();
+
+        let mut page_addrs = Vec::new();
+        for vm_mapping in inner
+            .vm_mappings
+            .find(&(start_addr..ROOT_VMAR_CAP_ADDR))
+            .filter(|vm_mapping| vm_mapping.is_mergeable())
+        {
+            let remaining = max_pages - page_addrs.len();
+            let Ok(mapped_pages) =
+                vm_mapping.mapped_pages_from(&self.vm_space, start_addr, remaining)
+            else {
+                continue;
+            };
+            page_addrs.extend(mapped_pages);
+
+            if page_addrs.len() == max_pages {
+                let next_addr = page_addrs
+                    .last()
+                    .map_or(start_addr, |addr| addr + PAGE_SIZE);
+                return (page_addrs, Some(next_addr));
+            }
+        }
+
+        (page_addrs, None)
+    }
+
+    fn replace_mergeable_page(
+        &self,
+        page_addr: Vaddr,
+        replace: impl FnOnce(&UFrame) -> Option<UFrame>,
+    ) -> Result<()> {
+        let inner = self.inner.read();
+        let Some(vm_mapping) = inner
+            .vm_mappings
+            .find_one(&page_addr)
+            .filter(|vm_mapping| vm_mapping.is_mergeable())
+        else {
+            // The mapping may have been changed since the page was found.
+            return Ok(());
+        };
+        vm_mapping.replace_page(&self.vm_space, page_addr, replace)
+    }
+
+    fn unmerge_pages(
+        &self,
+        range: Range<Vaddr>,
+        is_merged_page: impl Fn(&UFrame) -> bool,
+    ) -> Result<()> {
+        let inner = self.inner.read();
+        for vm_mapping in inner.vm_mappings.find(&range) {
+            vm_mapping.copy_shared_pages(&self.vm_space, &range, &is_merged_page)?;
+        }
+        Ok(())
+    }
+
+    fn page_out(&self, range: Range<Vaddr>) -> Result<()> {
+        let inner = self.inner.read();
+
+        if inner
+            .vm_mappings
+            .find(&range)
+            .any(|vm_mapping| vm_mapping.is_locked())
+        {
+            return_errno_with_message!(
+                Errno::EINVAL,
+                "the pages of locked mappings cannot be paged out"
+            );
+        }
+        check_fully_mapped(&inner, &range)?;
+
+        for vm_mapping in inner
+            .vm_mappings
+            .find(&range)
+            .filter(|vm_mapping| vm_mapping.is_swappable())
+        {
+            let max_pages = range.len() / PAGE_SIZE;
+            let page_addrs =
+                vm_mapping.mapped_pages_from(&self.vm_space, range.start, max_pages)?;
+            for page_addr in page_addrs.into_iter().take_while(|addr| *addr < range.end) {
+                match vm_mapping.swap_out_page(&self.vm_space, page_addr, true) {
+                    Ok(_) => {}
+                    // Like Linux, the remaining pages are kept if the swap areas are full (or
+                    // if there are no active swap areas).
+                    Err(err) if err.error() == Errno::ENOSPC => return Ok(()),
+                    Err(err) => return Err(err),
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    fn swappable_pages(&self, start_addr: Vaddr, max_pages: usize) -> (Vec<Vaddr>, Option<Vaddr>) {
+        let inner = self.inner.read();
+
+        let mut page_addrs = Vec::new();
+        for vm_mapping in inner
+            .vm_mappings
+            .find(&(start_addr..ROOT_VMAR_CAP_ADDR))
+            .filter(|vm_mapping| vm_mapping.is_swappable())
+        {
+            let remaining = max_pages - page_addrs.len();
+            let Ok(mapped_pages) =
+                vm_mapping.mapped_pages_from(&self.vm_space, start_addr, remaining)
+            else {
+                continue;
+            };
+            page_addrs.extend(mapped_pages);
+
+            if page_addrs.len() == max_pages {
+                let next_addr = page_addrs
+                    .last()
+                    .map_or(start_addr, |addr| addr + PAGE_SIZE);
+                return (page_addrs, Some(next_addr));
+            }
+        }
+
+        (page_addrs, None)
+    }
+
+    fn swap_out_page(&self, page_addr: Vaddr) -> Result<bool> {
+        let inner = self.inner.read();
+        let Some(vm_mapping) = inner
+            .vm_mappings
+            .find_one(&page_addr)
+            .filter(|vm_mapping| vm_mapping.is_swappable())
+        else {
+            // The mapping may have been changed since the page was found.
+            return Ok(false);
+        };
+        vm_mapping.swap_out_page(&self.vm_space, page_addr, false)
+    }
+
+    fn swap_in_area(&self, area: &Arc<SwapArea>) -> Result<()> {
+        let inner = self.inner.read();
+        for vm_mapping in inner.vm_mappings.iter() {
+            vm_mapping.swap_in_area(&self.vm_space, area)?;
+        }
+        Ok(())
+    }
+
+    fn unregister_userfaultfd(
+        &self,
+        range: Range<Vaddr>,
+        userfaultfd: &Arc<Userfaultfd>,
+    ) -> Result<()> {
+        let mut inner = self.inner.write();
+
+        let mut vm_mapping_addrs = Vec::new();
+        for vm_mapping in inner.vm_mappings.find(&range) {
+            if !vm_mapping.is_registered_to(userfaultfd, UserfaultMode::empty()) {
+                continue;
+            }
+            vm_mapping.write_protect_userfault(&self.vm_space, &range, false)?;
+            vm_mapping_addrs.push(vm_mapping.map_to_addr());
+        }
+
+        inner.set_userfaultfd(&self.vm_space, &range, vm_mapping_addrs, None)
+    }
+
+    fn write_protect_userfault(
+        &self,
+        range: Range<Vaddr>,
+        userfaultfd: &Arc<Userfaultfd>,
+        write_protect: bool,
+    ) -> Result<()> {
+        let inner = self.inner.read();
+
+        let mut vm_mappings = inner.vm_mappings.find(&range).peekable();
+        if vm_mappings.peek().is_none() {
+            return_errno_with_message!(Errno::ENOENT, "the range is not mapped");
+        }
+        if !vm_mappings
+            .all(|vm_mapping| vm_mapping.is_registered_to(userfaultfd, UserfaultMode::WP))
+        {
+            return_errno_with_message!(
+                Errno::ENOENT,
+                "the range is not registered in the write-protect mode"
+            );
+        }
+
+        for vm_mapping in inner.vm_mappings.find(&range) {
+            vm_mapping.write_protect_userfault(&self.vm_space, &range, write_protect)?;
+        }
+
+        Ok(())
+    }
+
+    fn fill_userfault_page(
+        &self,
+        page_addr: Vaddr,
+        userfaultfd: &Arc<Userfaultfd>,
+        frame: UFrame,
+        write_protect: bool,
+    ) -> Result<()> {
+        let inner = self.inner.read();
+
+        let Some(vm_mapping) = inner
+            .vm_mappings
+            .find_one(&page_addr)
+            .filter(|vm_mapping| vm_mapping.is_registered_to(userfaultfd, UserfaultMode::empty()))
+        else {
+            return_errno_with_message!(
+                Errno::ENOENT,
+                "the page is not registered to the userfaultfd"
+            );
+        };
+        if write_protect && !vm_mapping.is_registered_to(userfaultfd, UserfaultMode::WP) {
+            return_errno_with_message!(
+                Errno::EINVAL,
+                "the page is not registered in the write-protect mode"
+            );
+        }
+
+        vm_mapping.fill_userfault_page(&self.vm_space, page_addr, frame, write_protect)
+    }
+
     /// Clears all content of the root VMAR.
     fn clear_root_vmar(&self) -> Result<()> {
         self.vm_space.clear().unwrap();
@@ -367,6 +1615,102 @@ impl Vmar_ {
         Ok(())
     }
 
+    fn remap(
+        &self,
+        old_range: Range<Vaddr>,
+        new_addr: Option<Vaddr>,
+        new_size: usize,
+        may_move: bool,
+    ) -> Result<Vaddr> {
+        debug_assert!(old_range.start % PAGE_SIZE == 0 && old_range.end % PAGE_SIZE == 0);
+        debug_assert!(new_size % PAGE_SIZE == 0 && new_size > 0);
+
+        let mut inner = self.inner.write();
+        let old_size = old_range.len();
+
+        let find_old_mapping = |inner: &VmarInner| -> Result<(Vaddr, Vaddr)> {
+            let vm_mapping = inner
+                .vm_mappings
+                .find_one(&old_range.start)
+                .filter(|vm_mapping| old_range.end <= vm_mapping.map_end());
+            let Some(vm_mapping) = vm_mapping else {
+                return_errno_with_message!(
+                    Errno::EFAULT,
+                    "the old range is not within one mapping"
+                );
+            };
+            Ok((vm_mapping.map_to_addr(), vm_mapping.map_end()))
+        };
+        let (mut vm_mapping_addr, vm_mapping_end) = find_old_mapping(&inner)?;
+
+        let new_addr = if let Some(new_addr) = new_addr {
+            // The new range does not overlap with the old range, but it may overlap with the
+            // rest of the old mapping, so the old mapping is looked up again.
+            inner.alloc_free_region_exact_truncate(&self.vm_space, new_addr, new_size)?;
+            vm_mapping_addr = find_old_mapping(&inner)?.0;
+            new_addr
+        } else if new_size <= old_size {
+            inner.alloc_free_region_exact_truncate(
+                &self.vm_space,
+                old_range.start + new_size,
+                old_size - new_size,
+            )?;
+            return Ok(old_range.start);
+        } else if old_range.end == vm_mapping_end
+            && old_range
+                .start
+                .checked_add(new_size)
+                .is_some_and(|end| end <= self.base + self.size)
+            && inner
+                .alloc_free_region_exact(old_range.end, new_size - old_size)
+                .is_ok()
+        {
+            let vm_mapping = inner.vm_mappings.remove(&vm_mapping_addr).unwrap();
+            inner
+                .vm_mappings
+                .insert(vm_mapping.enlarge(new_size - old_size));
+            return Ok(old_range.start);
+        } else if may_move {
+            inner.alloc_free_region(new_size, PAGE_SIZE)?.start
+        } else {
+            return_errno_with_message!(Errno::ENOMEM, "the mapping cannot be expanded in place");
+        };
+
+        let vm_mapping = inner.vm_mappings.find_one(&vm_mapping_addr).unwrap();
+        vm_mapping.split_huge_pages_across(&self.vm_space, &old_range)?;
+        if new_size < old_size {
+            let new_range = old_range.start..old_range.start + new_size;
+            vm_mapping.split_huge_pages_across(&self.vm_space, &new_range)?;
+        }
+
+        let vm_mapping = inner.vm_mappings.remove(&vm_mapping_addr).unwrap();
+        let (left, taken, right) = vm_mapping.split_range(&old_range)?;
+        if let Some(left) = left {
+            inner.vm_mappings.insert(left);
+        }
+        if let Some(right) = right {
+            inner.vm_mappings.insert(right);
+        }
+
+        // Shrink the mapping before moving it, and enlarge it after moving it, so that only
+        // the pages in the old range are moved.
+        let taken = if new_size < old_size {
+            let (_, taken, tail) =
+                taken.split_range(&(old_range.start..old_range.start + new_size))?;
+            tail.unwrap().unmap(&self.vm_space)?;
+            taken
+        } else {
+            taken
+        };
+        let mut moved = taken.remap(&self.vm_space, new_addr)?;
+        if new_size > old_size {
+            moved = moved.enlarge(new_size - old_size);
+        }
+        inner.vm_mappings.insert(moved);
+
+        Ok(new_addr)
+    }
+
     /// Returns the attached `VmSpace`.
     fn vm_space(&self) -> &Arc<VmSpace> {
         &self.vm_space
@@ -383,6 +1727,8 @@ impl Vmar_ {
         {
             let inner = self.inner.read();
             let mut new_inner = new_vmar_.inner.write();
+            new_inner.mmap_base = inner.mmap_base;
+            new_inner.mmap_top = inner.mmap_top;
 
             // Clone mappings.
             let new_vmspace = new_vmar_.vm_space();
@@ -397,6 +1743,13 @@ impl Vmar_ {
                 let new_mapping = vm_mapping.new_fork()?;
                 new_inner.vm_mappings.insert(new_mapping);
 
+                // The pages of a shared mapping all come from its VMO (e.g., the page cache),
+                // so the new page table does not need to be populated. They are mapped on
+                // demand when the child accesses them, without affecting the parent.
+                if vm_mapping.is_shared() {
+                    continue;
+                }
+
                 // Protect the mapping and copy to the new page table for COW.
                 cur_cursor.jump(base).unwrap();
                 new_cursor.jump(base).unwrap();
@@ -452,6 +1805,8 @@ pub struct VmarMapOptions<R1, R2> {
     is_shared: bool,
     // Whether the mapping needs to handle surrounding pages when handling page fault.
     handle_page_faults_around: bool,
+    // Whether the mapping grows down when the pages below it are accessed.
+    grows_down: bool,
 }
 
 impl<R1, R2> VmarMapOptions<R1, R2> {
@@ -474,6 +1829,7 @@ impl<R1, R2> VmarMapOptions<R1, R2> {
             can_overwrite: false,
             is_shared: false,
             handle_page_faults_around: false,
+            grows_down: false,
         }
     }
 
@@ -566,6 +1922,16 @@ impl<R1, R2> VmarMapOptions<R1, R2> {
         self
     }
 
+    /// Sets whether the mapping grows down when the pages below it are accessed.
+    ///
+    /// The default value is false.
+    ///
+    /// If this option is set to true, the mapping must not be backed by a VMO.
+    pub fn grows_down(mut self, grows_down: bool) -> Self {
+        self.grows_down = grows_down;
+        self
+    }
+
     /// Creates the mapping and adds it to the parent VMAR.
     ///
     /// All options will be checked at this point.
@@ -585,6 +1951,7 @@ impl<R1, R2> VmarMapOptions<R1, R2> {
             can_overwrite,
             is_shared,
             handle_page_faults_around,
+            grows_down,
         } = self;
 
         // Allocates a free region.
@@ -614,8 +1981,10 @@ impl<R1, R2> VmarMapOptions<R1, R2> {
             vmo,
             is_shared,
             handle_page_faults_around,
+            grows_down,
             perms,
-        );
+        )
+        .set_locked(inner.future_lock.is_some());
 
         // Add the mapping to the VMAR.
         inner.vm_mappings.insert(vm_mapping);
@@ -645,6 +2014,9 @@ impl<R1, R2> VmarMapOptions<R1, R2> {
                 return_errno_with_message!(Errno::EINVAL, "invalid offset");
             }
         }
+        if self.grows_down && self.vmo.is_some() {
+            return_errno_with_message!(Errno::EINVAL, "a mapping backed by a VMO cannot grow down");
+        }
         self.check_perms()?;
         Ok(())
     }
@@ -660,6 +2032,21 @@ impl<R1, R2> VmarMapOptions<R1, R2> {
     }
 }
 
+/// Returns an error with [`ENOMEM`] if `range` contains pages that are not mapped.
+///
+/// [`ENOMEM`]: Errno::ENOMEM
+fn check_fully_mapped(inner: &VmarInner, range: &Range<Vaddr>) -> Result<()> {
+    let mapped_size: usize = inner
+        .vm_mappings
+        .find(range)
+        .map(|vm_mapping| get_intersected_range(range, &vm_mapping.range()).len())
+        .sum();
+    if mapped_size != range.len() {
+        return_errno_with_message!(Errno::ENOMEM, "the range contains unmapped pages");
+    }
+    Ok(())
+}
+
 /// Determines whether two ranges are intersected.
 /// returns false if one of the ranges has a length of 0
 pub fn is_intersected(range1: &Range<usize>, range2: &Range<usize>) -> bool {
@@ -8,15 +8,26 @@ use core::{
 
 use align_ext::AlignExt;
 use ostd::mm::{
-    tlb::TlbFlushOp, vm_space::VmItem, CachePolicy, FrameAllocOptions, PageFlags, PageProperty,
-    UFrame, VmSpace,
+    numa, tlb::TlbFlushOp, vm_space::VmItem, CachePolicy, FrameAllocOptions, Paddr, PageFlags,
+    PageProperty, UFrame, UntypedMem, VmSpace,
 };
 
-use super::interval_set::Interval;
+use super::{get_intersected_range, interval_set::Interval, MappedPageInfo, MappingStat};
 use crate::{
     prelude::*,
     thread::exception::PageFaultInfo,
-    vm::{perms::VmPerms, util::duplicate_frame, vmo::Vmo},
+    vm::{
+        memcg::{alloc_user_frame, alloc_user_huge_frame},
+        mempolicy::MemoryPolicy,
+        perms::VmPerms,
+        swap::{self, SwapArea, SwapSlot},
+        thp::{split_huge_frame, HugePageAdvice, HUGE_PAGE_SIZE, MAX_PTES_NONE},
+        userfaultfd::{
+            UserfaultFlags, UserfaultMode, Userfaultfd, UserfaultfdRegistration, PAGE_FLAG_UFFD_WP,
+        },
+        util::{duplicate_frame, is_zero_frame, zero_frame},
+        vmo::Vmo,
+    },
 };
 
 /// Mapping a range of physical pages into a `Vmar`.
@@ -58,6 +69,33 @@ pub(super) struct VmMapping {
     /// Whether the mapping needs to handle surrounding pages when handling
     /// page fault.
     handle_page_faults_around: bool,
+    /// Whether the mapping grows down when the pages below it are accessed.
+    ///
+    /// Such mappings (e.g., stacks) are always anonymous mappings.
+    grows_down: bool,
+    /// The userfaultfd that handles the page faults of the mapping.
+    ///
+    /// Only private anonymous mappings can be registered to a userfaultfd.
+    userfaultfd: Option<UserfaultfdRegistration>,
+    /// The NUMA memory policy of the mapping.
+    ///
+    /// If this field is `None`, the memory policy of the faulting thread is used.
+    memory_policy: Option<Arc<MemoryPolicy>>,
+    /// Whether the pages of the mapping can be merged by KSM.
+    ///
+    /// Only the pages of private anonymous mappings are actually merged.
+    is_mergeable: bool,
+    /// Whether the pages of the mapping are locked in the memory.
+    ///
+    /// The pages of a locked mapping cannot be discarded by `madvise` or swapped out.
+    is_locked: bool,
+    /// The advice on whether the mapping should be backed by huge pages.
+    huge_page_advice: HugePageAdvice,
+    /// The swapped-out pages of the mapping, indexed by their addresses.
+    ///
+    /// Only the pages of private anonymous mappings are swapped out. The entries are changed
+    /// with the page tables of the pages locked, so they are consistent with the page tables.
+    swapped_pages: SpinLock<BTreeMap<Vaddr, Arc<SwapSlot>>>,
     /// The permissions of pages in the mapping.
     ///
     /// All pages within the same `VmMapping` have the same permissions.
@@ -79,14 +117,23 @@ impl VmMapping {
         vmo: Option<MappedVmo>,
         is_shared: bool,
         handle_page_faults_around: bool,
+        grows_down: bool,
         perms: VmPerms,
     ) -> Self {
+        debug_assert!(!grows_down || vmo.is_none());
         Self {
             map_size,
             map_to_addr,
             vmo,
             is_shared,
             handle_page_faults_around,
+            grows_down,
+            userfaultfd: None,
+            memory_policy: None,
+            is_mergeable: false,
+            is_locked: false,
+            huge_page_advice: HugePageAdvice::None,
+            swapped_pages: SpinLock::new(BTreeMap::new()),
             perms,
         }
     }
@@ -94,6 +141,14 @@ impl VmMapping {
     pub(super) fn new_fork(&self) -> Result<VmMapping> {
         Ok(VmMapping {
             vmo: self.vmo.as_ref().map(|vmo| vmo.dup()).transpose()?,
+            // Like Linux, the child process does not inherit the registrations.
+            userfaultfd: None,
+            memory_policy: self.memory_policy.clone(),
+            // Like Linux, the child process does not inherit the memory locks.
+            is_locked: false,
+            // The swapped-out pages are shared until they are swapped in, like the mapped
+            // pages that are shared until they are copied on write.
+            swapped_pages: SpinLock::new(self.swapped_pages.lock().clone()),
             ..*self
         })
     }
@@ -113,6 +168,81 @@ impl VmMapping {
         self.map_size.get()
     }
 
+    /// Returns the mapped VMO and the offset in the VMO where the mapping starts.
+    pub(super) fn mapped_vmo(&self) -> Option<(&Vmo, usize)> {
+        self.vmo
+            .as_ref()
+            .map(|mapped_vmo| (&mapped_vmo.vmo, mapped_vmo.range.start))
+    }
+
+    /// Returns whether the mapping is shared among processes.
+    pub(super) fn is_shared(&self) -> bool {
+        self.is_shared
+    }
+
+    /// Returns whether the mapping grows down when the pages below it are accessed.
+    pub(super) fn grows_down(&self) -> bool {
+        self.grows_down
+    }
+
+    /// Returns whether the mapping is a private anonymous mapping.
+    pub(super) fn is_private_anonymous(&self) -> bool {
+        self.vmo.is_none()
+    }
+
+    /// Returns the registration of the mapping to a userfaultfd.
+    pub(super) fn userfaultfd(&self) -> Option<&UserfaultfdRegistration> {
+        self.userfaultfd.as_ref()
+    }
+
+    /// Returns whether the mapping is registered to `userfaultfd` with all kinds in `mode`.
+    pub(super) fn is_registered_to(
+        &self,
+        userfaultfd: &Arc<Userfaultfd>,
+        mode: UserfaultMode,
+    ) -> bool {
+        self.userfaultfd.as_ref().is_some_and(|registration| {
+            Arc::ptr_eq(registration.userfaultfd(), userfaultfd)
+                && registration.mode().contains(mode)
+        })
+    }
+
+    /// Returns the NUMA memory policy of the mapping.
+    pub(super) fn memory_policy(&self) -> Option<&Arc<MemoryPolicy>> {
+        self.memory_policy.as_ref()
+    }
+
+    /// Returns whether the pages of the mapping can be merged by KSM.
+    pub(super) fn is_mergeable(&self) -> bool {
+        self.is_mergeable && self.is_private_anonymous()
+    }
+
+    /// Returns whether the pages of the mapping are locked in the memory.
+    pub(super) fn is_locked(&self) -> bool {
+        self.is_locked
+    }
+
+    /// Returns the advice on whether the mapping should be backed by huge pages.
+    pub(super) fn huge_page_advice(&self) -> HugePageAdvice {
+        self.huge_page_advice
+    }
+
+    /// Returns whether the pages of the mapping can be swapped out.
+    ///
+    /// The pages registered to a userfaultfd are not swapped out, since swapping them in would
+    /// bypass the userfaultfd.
+    pub(super) fn is_swappable(&self) -> bool {
+        self.is_private_anonymous() && !self.is_locked && self.userfaultfd.is_none()
+    }
+
+    /// Returns the swap slot of the page at `page_addr` if the page is swapped out.
+    fn swapped_page(&self, page_addr: Vaddr) -> Option<Arc<SwapSlot>> {
+        if self.vmo.is_some() {
+            return None;
+        }
+        self.swapped_pages.lock().get(&page_addr).cloned()
+    }
+
     // Returns the permissions of pages in the mapping.
     pub fn perms(&self) -> VmPerms {
         self.perms
@@ -142,6 +272,16 @@ impl VmMapping {
         let page_aligned_addr = address.align_down(PAGE_SIZE);
         let is_write = page_fault_info.required_perms.contains(VmPerms::WRITE);
 
+        if self.swap_in_page(vm_space, page_aligned_addr)? {
+            return Ok(());
+        }
+
+        if let Some(block) = self.huge_page_block(address)
+            && self.handle_huge_page_fault(vm_space, &block, page_fault_info)?
+        {
+            return Ok(());
+        }
+
         if !is_write && self.vmo.is_some() && self.handle_page_faults_around {
             self.handle_page_faults_around(vm_space, address)?;
             return Ok(());
@@ -179,12 +319,16 @@ impl VmMapping {
 
                 let new_flags = PageFlags::W | PageFlags::ACCESSED | PageFlags::DIRTY;
 
+                if self.is_shared {
+                    self.mark_page_dirty(va)?;
+                }
+
                 if self.is_shared || only_reference {
                     cursor.protect_next(PAGE_SIZE, |p| p.flags |= new_flags);
                     cursor.flusher().issue_tlb_flush(TlbFlushOp::Address(va));
                     cursor.flusher().dispatch_tlb_flush();
                 } else {
-                    let new_frame = duplicate_frame(&frame)?;
+                    let new_frame = duplicate_frame(&frame, self.memory_policy.as_deref())?;
                     prop.flags |= new_flags;
                     cursor.map(new_frame.into(), prop);
                 }
@@ -197,7 +341,7 @@ impl VmMapping {
                 let vm_perms = {
                     let mut perms = self.perms;
                     if is_readonly {
-                        // COW pages are forced to be read-only.
+                        // COW pages and clean shared pages are forced to be read-only.
                         perms -= VmPerms::WRITE;
                     }
                     perms
@@ -216,17 +360,354 @@ impl VmMapping {
         Ok(())
     }
 
+    /// Checks whether the page fault should be handled by the registered userfaultfd.
+    ///
+    /// If so, returns the userfaultfd and the flags that describe the page fault.
+    pub(super) fn check_userfault(
+        &self,
+        vm_space: &VmSpace,
+        page_fault_info: &PageFaultInfo,
+    ) -> Option<(Arc<Userfaultfd>, UserfaultFlags)> {
+        let registration = self.userfaultfd.as_ref()?;
+        if registration.userfaultfd().is_released()
+            || !self.perms.contains(page_fault_info.required_perms)
+        {
+            return None;
+        }
+
+        let page_addr = page_fault_info.address.align_down(PAGE_SIZE);
+        let is_write = page_fault_info.required_perms.contains(VmPerms::WRITE);
+        let mode = registration.mode();
+
+        let mut cursor = vm_space.cursor(&(page_addr..page_addr + PAGE_SIZE)).ok()?;
+        let flags = match cursor.query().ok()? {
+            VmItem::NotMapped { .. }
+                if mode.contains(UserfaultMode::MISSING)
+                    && self.swapped_page(page_addr).is_none() =>
+            {
+                if is_write {
+                    UserfaultFlags::WRITE
+                } else {
+                    UserfaultFlags::empty()
+                }
+            }
+            VmItem::Mapped { prop, .. }
+                if is_write
+                    && prop.flags.contains(PAGE_FLAG_UFFD_WP)
+                    && mode.contains(UserfaultMode::WP) =>
+            {
+                UserfaultFlags::WRITE | UserfaultFlags::WP
+            }
+            _ => return None,
+        };
+
+        Some((registration.userfaultfd().clone(), flags))
+    }
+
+    /// Reads or writes the memory at `addr` on behalf of another process.
+    ///
+    /// The accessed bytes must be within one page.
+    pub(super) fn access_remote(
+        &self,
+        vm_space: &VmSpace,
+        addr: Vaddr,
+        buf: &mut [u8],
+        is_write: bool,
+    ) -> Result<()> {
+        let page_addr = addr.align_down(PAGE_SIZE);
+        debug_assert!(addr + buf.len() <= page_addr + PAGE_SIZE);
+
+        if !self.perms.contains(VmPerms::READ) {
+            return_errno_with_message!(Errno::EIO, "the mapping is not accessible");
+        }
+        let is_forced_write = is_write && !self.perms.contains(VmPerms::WRITE);
+        if is_forced_write && self.is_shared {
+            return_errno_with_message!(Errno::EIO, "the shared mapping is not writable");
+        }
+
+        // Commit the page in the same way as the user space accesses it.
+        let required_perms = if is_write && !is_forced_write {
+            VmPerms::READ | VmPerms::WRITE
+        } else {
+            VmPerms::READ
+        };
+        self.handle_page_fault(
+            vm_space,
+            &PageFaultInfo {
+                address: addr,
+                required_perms,
+            },
+        )?;
+        if is_forced_write {
+            // Only the written base page is copied.
+            self.split_huge_page(vm_space, addr)?;
+        }
+
+        let mut cursor = vm_space.cursor_mut(&(page_addr..page_addr + PAGE_SIZE))?;
+        let VmItem::Mapped {
+            frame, mut prop, ..
+        } = cursor.query().unwrap()
+        else {
+            return_errno_with_message!(Errno::EIO, "the page is not mapped");
+        };
+
+        // The frame may be a huge frame, which is mapped at an address aligned to its size.
+        let offset = addr % frame.size();
+        if !is_write {
+            frame.read_bytes(offset, buf)?;
+            return Ok(());
+        }
+
+        // Similar to the page fault handler, the frame can be written directly if we are its
+        // only reference.
+        let frame = if is_forced_write && frame.reference_count() != 2 {
+            // Perform COW, but keep the page read-only for the user space.
+            let new_frame: UFrame = duplicate_frame(&frame, self.memory_policy.as_deref())?.into();
+            prop.flags |= PageFlags::ACCESSED | PageFlags::DIRTY;
+            cursor.map(new_frame.clone(), prop);
+            new_frame
+        } else {
+            frame
+        };
+        frame.write_bytes(offset, buf)?;
+
+        Ok(())
+    }
+
+    /// Writes the pages of the shared mapping within `range` back to the mapped VMO's pager.
+    ///
+    /// The pages are write-protected before being written back, so that the following writes
+    /// will mark them as dirty again.
+    pub(super) fn sync(&self, vm_space: &VmSpace, range: &Range<Vaddr>) -> Result<()> {
+        if !self.is_shared {
+            return Ok(());
+        }
+        let Some(vmo) = &self.vmo else {
+            return Ok(());
+        };
+
+        let range = get_intersected_range(range, &self.range());
+        let mut cursor = vm_space.cursor_mut(&range)?;
+        while cursor.virt_addr() < range.end {
+            let op = |p: &mut PageProperty| p.flags -= PageFlags::W;
+            if let Some(va) = cursor.protect_next(range.end - cursor.virt_addr(), op) {
+                cursor.flusher().issue_tlb_flush(TlbFlushOp::Range(va));
+            } else {
+                break;
+            }
+        }
+        cursor.flusher().dispatch_tlb_flush();
+        drop(cursor);
+
+        vmo.write_back(&((range.start - self.map_to_addr)..(range.end - self.map_to_addr)))
+    }
+
+    /// Returns the NUMA node of the frame that is mapped at `addr`.
+    ///
+    /// The page is committed in the same way as the user space reads it.
+    pub(super) fn node_of_page(&self, vm_space: &VmSpace, addr: Vaddr) -> Result<usize> {
+        if !self.perms.contains(VmPerms::READ) {
+            return_errno_with_message!(Errno::EFAULT, "the mapping is not readable");
+        }
+        self.handle_page_fault(
+            vm_space,
+            &PageFaultInfo {
+                address: addr,
+                required_perms: VmPerms::READ,
+            },
+        )?;
+
+        let page_addr = addr.align_down(PAGE_SIZE);
+        let mut cursor = vm_space.cursor(&(page_addr..page_addr + PAGE_SIZE))?;
+        let VmItem::Mapped { frame, .. } = cursor.query()? else {
+            return_errno_with_message!(Errno::EFAULT, "the page is not mapped");
+        };
+        Ok(numa::node_of_paddr(frame.start_paddr()))
+    }
+
+    pub(super) fn read_page_for_dump(
+        &self,
+        vm_space: &VmSpace,
+        page_addr: Vaddr,
+        buf: &mut [u8],
+    ) -> Result<()> {
+        if self.vmo.is_some() {
+            return self.access_remote(vm_space, page_addr, buf, false);
+        }
+
+        // The pages of anonymous mappings are committed only when they are accessed.
+        let item = vm_space
+            .cursor(&(page_addr..page_addr + PAGE_SIZE))?
+            .query()?;
+        match item {
+            VmItem::Mapped { frame, .. } => frame.read_bytes(page_addr % frame.size(), buf)?,
+            VmItem::NotMapped { .. } => match self.swapped_page(page_addr) {
+                // The swapped-out page is read without being swapped in.
+                Some(slot) => slot.read(&mut VmWriter::from(buf).to_fallible())?,
+                None => buf.fill(0),
+            },
+        }
+        Ok(())
+    }
+
+    /// Reads the pages of the mapped VMO within `range` in advance.
+    ///
+    /// The pages are committed in the VMO (e.g., read into the page cache) but are not mapped.
+    pub(super) fn read_ahead(&self, range: &Range<Vaddr>) {
+        let Some(vmo) = &self.vmo else {
+            return;
+        };
+
+        let range = get_intersected_range(range, &self.range());
+        let start_offset = range.start - self.map_to_addr;
+        let end_offset = (range.end - self.map_to_addr).min(vmo.size());
+        if start_offset >= end_offset {
+            return;
+        }
+
+        // Like Linux, failures of reading ahead are ignored.
+        let operate = |commit_fn: &mut dyn FnMut() -> Result<UFrame>| commit_fn().map(|_| ());
+        let _ = vmo.operate_on_range(&(start_offset..end_offset), operate);
+    }
+
+    /// Collects the statistics of the mapping and the pages mapped in it.
+    pub(super) fn stat(&self, vm_space: &VmSpace) -> Result<MappingStat> {
+        let mut stat = MappingStat {
+            range: self.range(),
+            perms: self.perms,
+            is_shared: self.is_shared,
+            vmo_offset: self.vmo.as_ref().map(|vmo| vmo.range.start),
+            grows_down: self.grows_down,
+            is_locked: self.is_locked,
+            is_mergeable: self.is_mergeable(),
+            huge_page_advice: self.huge_page_advice,
+            rss: 0,
+            pss: 0,
+            shared_clean: 0,
+            shared_dirty: 0,
+            private_clean: 0,
+            private_dirty: 0,
+            referenced: 0,
+            anonymous: 0,
+            swap: 0,
+            swap_pss: 0,
+        };
+
+        for slot in self.swapped_pages.lock().values() {
+            stat.swap += PAGE_SIZE;
+            stat.swap_pss += PAGE_SIZE / Arc::strong_count(slot);
+        }
+
+        for item in vm_space.cursor(&self.range())? {
+            let VmItem::Mapped { frame, prop, .. } = item else {
+                continue;
+            };
+            if is_zero_frame(&frame) {
+                continue;
+            }
+
+            let size = frame.size();
+            let map_count = self.estimate_map_count(&frame);
+            let is_dirty = prop.flags.contains(PageFlags::DIRTY);
+            stat.rss += size;
+            stat.pss += size / map_count;
+            match (map_count > 1, is_dirty) {
+                (true, false) => stat.shared_clean += size,
+                (true, true) => stat.shared_dirty += size,
+                (false, false) => stat.private_clean += size,
+                (false, true) => stat.private_dirty += size,
+            }
+            if prop.flags.contains(PageFlags::ACCESSED) {
+                stat.referenced += size;
+            }
+            if self.is_private_anonymous() || (!self.is_shared && is_dirty) {
+                stat.anonymous += size;
+            }
+        }
+
+        Ok(stat)
+    }
+
+    /// Returns the information of the pages mapped within `range`.
+    pub(super) fn query_pages(
+        &self,
+        vm_space: &VmSpace,
+        range: &Range<Vaddr>,
+    ) -> Result<Vec<(Vaddr, MappedPageInfo)>> {
+        let range = get_intersected_range(range, &self.range());
+        let mut pages = Vec::new();
+        for item in vm_space.cursor(&range)? {
+            let VmItem::Mapped { va, frame, .. } = item else {
+                continue;
+            };
+            let is_exclusive = self.estimate_map_count(&frame) == 1;
+            pages.extend(base_pages_of(va, &frame, &range).map(|(page_addr, paddr)| {
+                (
+                    page_addr,
+                    MappedPageInfo {
+                        paddr,
+                        is_file_or_shared: self.vmo.is_some(),
+                        is_exclusive,
+                    },
+                )
+            }));
+        }
+        Ok(pages)
+    }
+
+    /// Estimates the number of mappings of `frame` from its reference count.
+    ///
+    /// The reference held by the caller is excluded, and so is the reference held by the page
+    /// cache if the mapping is backed by a VMO. The result is always at least one.
+    fn estimate_map_count(&self, frame: &UFrame) -> usize {
+        let mut other_refs = 1;
+        if self.vmo.is_some() {
+            other_refs += 1;
+        }
+        (frame.reference_count() as usize)
+            .saturating_sub(other_refs)
+            .max(1)
+    }
+
+    /// Commits and maps the pages within `range` as if they were accessed by the user space.
+    ///
+    /// The pages of writable private mappings are committed for writing, so that they do not
+    /// need to be copied later. Inaccessible mappings are skipped.
+    pub(super) fn populate(&self, vm_space: &VmSpace, range: &Range<Vaddr>) -> Result<()> {
+        if !self.perms.contains(VmPerms::READ) {
+            return Ok(());
+        }
+        let required_perms = if self.perms.contains(VmPerms::WRITE) && !self.is_shared {
+            VmPerms::READ | VmPerms::WRITE
+        } else {
+            VmPerms::READ
+        };
+
+        let range = get_intersected_range(range, &self.range());
+        for address in range.step_by(PAGE_SIZE) {
+            self.handle_page_fault(
+                vm_space,
+                &PageFaultInfo {
+                    address,
+                    required_perms,
+                },
+            )?;
+        }
+
+        Ok(())
+    }
+
     fn prepare_page(&self, page_fault_addr: Vaddr, write: bool) -> Result<(UFrame, bool)> {
         let mut is_readonly = false;
         let Some(vmo) = &self.vmo else {
-            return Ok((FrameAllocOptions::new().alloc_frame()?.into(), is_readonly));
+            return prepare_anonymous_page(write, self.memory_policy.as_deref());
         };
 
         let page_offset = page_fault_addr.align_down(PAGE_SIZE) - self.map_to_addr;
         let Ok(page) = vmo.get_committed_frame(page_offset) else {
             if !self.is_shared {
                 // The page index is outside the VMO. This is only allowed in private mapping.
-                return Ok((FrameAllocOptions::new().alloc_frame()?.into(), is_readonly));
+                return prepare_anonymous_page(write, self.memory_policy.as_deref());
             } else {
                 return_errno_with_message!(
                     Errno::EFAULT,
@@ -237,17 +718,34 @@ impl VmMapping {
 
         if !self.is_shared && write {
             // Write access to private VMO-backed mapping. Performs COW directly.
-            Ok((duplicate_frame(&page)?.into(), is_readonly))
+            Ok((
+                duplicate_frame(&page, self.memory_policy.as_deref())?.into(),
+                is_readonly,
+            ))
+        } else if write {
+            // Write access to shared VMO-backed mapping. The page should be written back.
+            vmo.mark_page_dirty(page_offset)?;
+            Ok((page, is_readonly))
         } else {
-            // Operations to shared mapping or read access to private VMO-backed mapping.
-            // If read access to private VMO-backed mapping triggers a page fault,
-            // the map should be readonly. If user next tries to write to the frame,
-            // another page fault will be triggered which will performs a COW (Copy-On-Write).
-            is_readonly = !self.is_shared;
+            // Read access to VMO-backed mapping. The map should be readonly. If user next
+            // tries to write to the frame, another page fault will be triggered which will
+            // perform a COW (Copy-On-Write) in private mapping, or mark the page as dirty in
+            // shared mapping.
+            is_readonly = true;
             Ok((page, is_readonly))
         }
     }
 
+    /// Marks the page at `addr` as dirty in the mapped VMO.
+    fn mark_page_dirty(&self, addr: Vaddr) -> Result<()> {
+        let Some(vmo) = &self.vmo else {
+            return Ok(());
+        };
+
+        let page_offset = addr.align_down(PAGE_SIZE) - self.map_to_addr;
+        vmo.mark_page_dirty(page_offset)
+    }
+
     fn handle_page_faults_around(&self, vm_space: &VmSpace, page_fault_addr: Vaddr) -> Result<()> {
         const SURROUNDING_PAGE_NUM: usize = 16;
         const SURROUNDING_PAGE_ADDR_MASK: usize = !(SURROUNDING_PAGE_NUM * PAGE_SIZE - 1);
@@ -290,13 +788,289 @@ impl VmMapping {
     }
 }
 
+/******************************* Huge pages **********************************/
+
+impl VmMapping {
+    /// Returns whether new huge pages can be mapped in the mapping.
+    ///
+    /// Only private anonymous mappings are backed by huge pages, unless they are advised not to
+    /// be. The mappings that grow down, that are registered to a userfaultfd, or whose pages can
+    /// be merged by KSM handle their pages one by one, so they are not backed by huge pages
+    /// either.
+    pub(super) fn is_huge_page_eligible(&self) -> bool {
+        self.is_private_anonymous()
+            && self.huge_page_advice != HugePageAdvice::NoHugePage
+            && !self.grows_down
+            && self.userfaultfd.is_none()
+            && !self.is_mergeable
+    }
+
+    /// Returns the huge-page-aligned block that contains `addr`, if the block is entirely
+    /// within the mapping and the mapping is a private anonymous mapping.
+    ///
+    /// Huge pages are only mapped in such blocks.
+    fn huge_page_block(&self, addr: Vaddr) -> Option<Range<Vaddr>> {
+        if !self.is_private_anonymous() {
+            return None;
+        }
+
+        let block_start = addr.align_down(HUGE_PAGE_SIZE);
+        let block = block_start..block_start + HUGE_PAGE_SIZE;
+        (self.map_to_addr <= block.start && block.end <= self.map_end()).then_some(block)
+    }
+
+    /// Returns whether any pages within `block` are swapped out.
+    fn has_swapped_pages(&self, block: &Range<Vaddr>) -> bool {
+        self.swapped_pages
+            .lock()
+            .range(block.clone())
+            .next()
+            .is_some()
+    }
+
+    /// Handles the page fault in `block` with a huge page.
+    ///
+    /// Returns `false` if the page fault should be handled with a base page instead.
+    fn handle_huge_page_fault(
+        &self,
+        vm_space: &VmSpace,
+        block: &Range<Vaddr>,
+        page_fault_info: &PageFaultInfo,
+    ) -> Result<bool> {
+        let is_write = page_fault_info.required_perms.contains(VmPerms::WRITE);
+        let mut cursor = vm_space.cursor_mut(block)?;
+
+        match cursor.query()? {
+            // Like Linux, a read access maps the zero frame as a base page, which may be
+            // collapsed into a huge page later.
+            VmItem::NotMapped { len, .. } if len == HUGE_PAGE_SIZE => {
+                if !is_write || !self.is_huge_page_eligible() || self.has_swapped_pages(block) {
+                    return Ok(false);
+                }
+                let Ok(frame) =
+                    alloc_user_huge_frame(&FrameAllocOptions::new(), self.memory_policy.as_deref())
+                else {
+                    return Ok(false);
+                };
+
+                let page_flags =
+                    PageFlags::from(self.perms) | PageFlags::ACCESSED | PageFlags::DIRTY;
+                cursor.map(
+                    frame.into(),
+                    PageProperty::new(page_flags, CachePolicy::Writeback),
+                );
+            }
+            VmItem::Mapped {
+                va,
+                frame,
+                mut prop,
+            } if frame.level() > 1 => {
+                // The page fault is already handled maybe by other threads.
+                if VmPerms::from(prop.flags).contains(page_fault_info.required_perms)
+                    || prop.flags.contains(PageFlags::W)
+                {
+                    TlbFlushOp::Address(va).perform_on_current();
+                    return Ok(true);
+                }
+                debug_assert!(is_write);
+
+                // Perform COW as the page fault handler does for base pages.
+                let new_flags = PageFlags::W | PageFlags::ACCESSED | PageFlags::DIRTY;
+                if frame.reference_count() == 2 {
+                    cursor.protect_next(HUGE_PAGE_SIZE, |p| p.flags |= new_flags);
+                    cursor.flusher().issue_tlb_flush(TlbFlushOp::Address(va));
+                    cursor.flusher().dispatch_tlb_flush();
+                    return Ok(true);
+                }
+                let Ok(new_frame) = duplicate_frame(&frame, self.memory_policy.as_deref()) else {
+                    // Copy the written base page only.
+                    drop(cursor);
+                    self.split_huge_page(vm_space, va)?;
+                    return Ok(false);
+                };
+                prop.flags |= new_flags;
+                cursor.map(new_frame.into(), prop);
+            }
+            _ => return Ok(false),
+        }
+
+        Ok(true)
+    }
+
+    /// Splits the huge page that contains `addr` into base pages, if there is one.
+    ///
+    /// If the huge page is only mapped here, it is split in place. Otherwise, its contents are
+    /// copied to new base pages, so that the other mappings of it are not affected.
+    pub(super) fn split_huge_page(&self, vm_space: &VmSpace, addr: Vaddr) -> Result<()> {
+        let Some(block) = self.huge_page_block(addr) else {
+            return Ok(());
+        };
+
+        let mut cursor = vm_space.cursor_mut(&block)?;
+        let VmItem::Mapped { frame, prop, .. } = cursor.query()? else {
+            return Ok(());
+        };
+        if frame.level() == 1 {
+            return Ok(());
+        }
+
+        // Unmap the huge page first, so that it is neither written during the split nor
+        // referenced by the page table.
+        cursor.unmap(HUGE_PAGE_SIZE);
+        cursor.jump(block.start)?;
+
+        match split_huge_frame(frame, self.memory_policy.as_deref()) {
+            Ok(base_frames) => {
+                for base_frame in base_frames {
+                    cursor.map(base_frame, prop);
+                }
+                Ok(())
+            }
+            Err((frame, err)) => {
+                cursor.map(frame, prop);
+                Err(err)
+            }
+        }
+    }
+
+    /// Splits the huge pages that cross the boundaries of `range`, so that the mapping can be
+    /// split at the boundaries.
+    pub(super) fn split_huge_pages_across(
+        &self,
+        vm_space: &VmSpace,
+        range: &Range<Vaddr>,
+    ) -> Result<()> {
+        self.split_huge_page_at(vm_space, range.start)?;
+        self.split_huge_page_at(vm_space, range.end)
+    }
+
+    /// Splits the huge page that crosses `addr`, so that `addr` is a boundary between pages.
+    fn split_huge_page_at(&self, vm_space: &VmSpace, addr: Vaddr) -> Result<()> {
+        if addr % HUGE_PAGE_SIZE == 0 {
+            return Ok(());
+        }
+        self.split_huge_page(vm_space, addr)
+    }
+
+    /// Splits all the huge pages within `range` into base pages.
+    pub(super) fn split_huge_pages(&self, vm_space: &VmSpace, range: &Range<Vaddr>) -> Result<()> {
+        let range = get_intersected_range(range, &self.range());
+        if !self.is_private_anonymous() || range.is_empty() {
+            return Ok(());
+        }
+
+        let huge_page_addrs: Vec<Vaddr> = vm_space
+            .cursor(&range)?
+            .filter_map(|item| match item {
+                VmItem::Mapped { va, frame, .. } if frame.level() > 1 => Some(va),
+                _ => None,
+            })
+            .collect();
+        for addr in huge_page_addrs {
+            self.split_huge_page(vm_space, addr)?;
+        }
+
+        Ok(())
+    }
+
+    /// Collapses the base pages mapped within `block` into a huge page, like `khugepaged` on
+    /// Linux.
+    ///
+    /// The pages are only collapsed if at most [`MAX_PTES_NONE`] of them are not mapped, and if
+    /// the mapped ones are only mapped here. The caller must prevent the page faults in the
+    /// mapping, since the page table that maps the base pages is replaced.
+    ///
+    /// Returns whether the pages are collapsed.
+    pub(super) fn collapse_huge_page(
+        &self,
+        vm_space: &VmSpace,
+        block: &Range<Vaddr>,
+    ) -> Result<bool> {
+        debug_assert!(self.is_huge_page_eligible());
+        debug_assert_eq!(self.huge_page_block(block.start).as_ref(), Some(block));
+
+        if self.has_swapped_pages(block) {
+            return Ok(false);
+        }
+
+        let mut base_pages = Vec::new();
+        for item in vm_space.cursor(block)? {
+            let VmItem::Mapped { va, frame, .. } = item else {
+                continue;
+            };
+            if frame.level() > 1 {
+                return Ok(false);
+            }
+            // The zero frame counts as an unmapped page.
+            if is_zero_frame(&frame) {
+                continue;
+            }
+            // One reference is held by the page table, and the other is held by `frame`.
+            if frame.reference_count() != 2 {
+                return Ok(false);
+            }
+            base_pages.push((va, frame));
+        }
+        if base_pages.len() + MAX_PTES_NONE < HUGE_PAGE_SIZE / PAGE_SIZE {
+            return Ok(false);
+        }
+
+        let huge_frame =
+            alloc_user_huge_frame(&FrameAllocOptions::new(), self.memory_policy.as_deref())?;
+
+        // Unmap the base pages first, so that they are not written while they are copied.
+        let mut cursor = vm_space.cursor_mut(block)?;
+        cursor.unmap(HUGE_PAGE_SIZE);
+        for (va, frame) in base_pages {
+            huge_frame
+                .writer()
+                .skip(va - block.start)
+                .write(&mut frame.reader());
+        }
+
+        // The huge page is only mapped here, so it can be writable if the mapping is writable.
+        let page_flags = PageFlags::from(self.perms) | PageFlags::ACCESSED | PageFlags::DIRTY;
+        cursor.jump(block.start)?;
+        cursor.map(
+            huge_frame.into(),
+            PageProperty::new(page_flags, CachePolicy::Writeback),
+        );
+
+        Ok(true)
+    }
+}
+
 /**************************** Transformations ********************************/
 
 impl VmMapping {
     /// Enlarges the mapping by `extra_size` bytes to the high end.
+    ///
+    /// If the mapped range of the VMO ends at the end of the mapping, it is enlarged as well.
     pub fn enlarge(self, extra_size: usize) -> Self {
+        let map_size = self.map_size.get();
+        let vmo = self.vmo.map(|mut vmo| {
+            if vmo.size() == map_size {
+                vmo.range.end += extra_size;
+            }
+            vmo
+        });
         Self {
-            map_size: NonZeroUsize::new(self.map_size.get() + extra_size).unwrap(),
+            map_size: NonZeroUsize::new(map_size + extra_size).unwrap(),
+            vmo,
+            ..self
+        }
+    }
+
+    /// Enlarges the mapping to the low end so that it starts at `new_start`.
+    ///
+    /// The mapping must grow down, so it is not backed by a VMO.
+    pub(super) fn grow_down(self, new_start: Vaddr) -> Self {
+        debug_assert!(self.grows_down && self.vmo.is_none());
+        debug_assert!(new_start < self.map_to_addr && new_start % PAGE_SIZE == 0);
+
+        Self {
+            map_size: NonZeroUsize::new(self.map_end() - new_start).unwrap(),
+            map_to_addr: new_start,
             ..self
         }
     }
@@ -321,18 +1095,25 @@ impl VmMapping {
             r_vmo = Some(MappedVmo::new(vmo.vmo.dup()?, r_range));
         }
 
+        let mut l_swapped_pages = core::mem::take(&mut *self.swapped_pages.lock());
+        let r_swapped_pages = l_swapped_pages.split_off(&at);
+
         let left_size = at - self.map_to_addr;
         let right_size = self.map_size.get() - left_size;
         let left = Self {
             map_to_addr: self.map_to_addr,
             map_size: NonZeroUsize::new(left_size).unwrap(),
             vmo: l_vmo,
+            userfaultfd: self.userfaultfd.clone(),
+            memory_policy: self.memory_policy.clone(),
+            swapped_pages: SpinLock::new(l_swapped_pages),
             ..self
         };
         let right = Self {
             map_to_addr: at,
             map_size: NonZeroUsize::new(right_size).unwrap(),
             vmo: r_vmo,
+            swapped_pages: SpinLock::new(r_swapped_pages),
             ..self
         };
 
@@ -349,6 +1130,9 @@ impl VmMapping {
     ///
     /// Returns (left outside, within, right outside) if successful.
     ///
+    /// The huge pages that cross the boundaries of `range` must have been split by
+    /// [`Self::split_huge_pages_across`].
+    ///
     /// # Panics
     ///
     /// Panics if the mapping does not contain the range, or if the start or
@@ -375,6 +1159,43 @@ impl VmMapping {
         }
         panic!("The mapping does not contain the splitting range.");
     }
+
+    /// Sets the registration of the mapping to a userfaultfd.
+    pub(super) fn set_userfaultfd(self, userfaultfd: Option<UserfaultfdRegistration>) -> Self {
+        Self {
+            userfaultfd,
+            ..self
+        }
+    }
+
+    /// Sets the NUMA memory policy of the mapping.
+    pub(super) fn set_memory_policy(self, memory_policy: Option<Arc<MemoryPolicy>>) -> Self {
+        Self {
+            memory_policy,
+            ..self
+        }
+    }
+
+    /// Sets whether the pages of the mapping can be merged by KSM.
+    pub(super) fn set_mergeable(self, is_mergeable: bool) -> Self {
+        Self {
+            is_mergeable,
+            ..self
+        }
+    }
+
+    /// Sets whether the pages of the mapping are locked in the memory.
+    pub(super) fn set_locked(self, is_locked: bool) -> Self {
+        Self { is_locked, ..self }
+    }
+
+    /// Sets the advice on whether the mapping should be backed by huge pages.
+    pub(super) fn set_huge_page_advice(self, huge_page_advice: HugePageAdvice) -> Self {
+        Self {
+            huge_page_advice,
+            ..self
+        }
+    }
 }
 
 /************************** VM Space operations ******************************/
@@ -389,13 +1210,382 @@ impl VmMapping {
         Ok(())
     }
 
+    /// Unmaps the pages within `range` from the VM space, while the mapping is kept.
+    ///
+    /// The following accesses will see the contents of the mapped VMO, or zeros if the pages
+    /// are not backed by the VMO. The swapped-out pages within `range` are discarded as well.
+    pub(super) fn discard_pages(&self, vm_space: &VmSpace, range: &Range<Vaddr>) -> Result<()> {
+        let range = get_intersected_range(range, &self.range());
+        self.split_huge_pages_across(vm_space, &range)?;
+
+        let mut cursor = vm_space.cursor_mut(&range)?;
+        cursor.unmap(range.len());
+
+        let discarded_pages = {
+            let mut swapped_pages = self.swapped_pages.lock();
+            let mut discarded_pages = swapped_pages.split_off(&range.start);
+            swapped_pages.append(&mut discarded_pages.split_off(&range.end));
+            discarded_pages
+        };
+        // Free the swap slots without holding the lock.
+        drop(discarded_pages);
+
+        Ok(())
+    }
+
+    /// Moves the mapping to `new_addr`, together with the pages mapped in the VM space.
+    ///
+    /// The new range must not be occupied.
+    pub(super) fn remap(self, vm_space: &VmSpace, new_addr: Vaddr) -> Result<Self> {
+        let range = self.range();
+        let new_range = new_addr..new_addr + range.len();
+
+        // The huge pages can only be moved as a whole if they are still aligned.
+        if new_addr.abs_diff(range.start) % HUGE_PAGE_SIZE != 0 {
+            self.split_huge_pages(vm_space, &range)?;
+        }
+
+        let mapped_pages: Vec<_> = vm_space
+            .cursor(&range)?
+            .filter_map(|item| match item {
+                VmItem::Mapped { va, frame, prop } => Some((va, frame, prop)),
+                VmItem::NotMapped { .. } => None,
+            })
+            .collect();
+        vm_space.cursor_mut(&range)?.unmap(range.len());
+
+        let mut cursor = vm_space.cursor_mut(&new_range)?;
+        for (va, frame, prop) in mapped_pages {
+            cursor.jump(va - range.start + new_range.start)?;
+            cursor.map(frame, prop);
+        }
+
+        let swapped_pages = core::mem::take(&mut *self.swapped_pages.lock())
+            .into_iter()
+            .map(|(addr, slot)| (addr - range.start + new_range.start, slot))
+            .collect();
+
+        Ok(Self {
+            map_to_addr: new_addr,
+            swapped_pages: SpinLock::new(swapped_pages),
+            ..self
+        })
+    }
+
+    /// Maps `frame` to the page at `page_addr` to resolve a missing-page fault for the
+    /// userfaultfd.
+    ///
+    /// If the page is write-protected for the userfaultfd, writes to it will be reported to
+    /// the userfaultfd.
+    pub(super) fn fill_userfault_page(
+        &self,
+        vm_space: &VmSpace,
+        page_addr: Vaddr,
+        frame: UFrame,
+        write_protect: bool,
+    ) -> Result<()> {
+        let mut cursor = vm_space.cursor_mut(&(page_addr..page_addr + PAGE_SIZE))?;
+        if let VmItem::Mapped { .. } = cursor.query()? {
+            return_errno_with_message!(Errno::EEXIST, "the page is already mapped");
+        }
+
+        let mut page_flags = PageFlags::from(self.perms) | PageFlags::ACCESSED;
+        // The zero frame must never be written, and the write-protected pages are made
+        // writable by the page fault handler after the protection is removed.
+        if write_protect || is_zero_frame(&frame) {
+            page_flags -= PageFlags::W;
+        }
+        if write_protect {
+            page_flags |= PAGE_FLAG_UFFD_WP;
+        }
+        cursor.map(frame, PageProperty::new(page_flags, CachePolicy::Writeback));
+
+        Ok(())
+    }
+
+    /// Write-protects the mapped pages within `range` for the userfaultfd, or removes the
+    /// protection.
+    ///
+    /// The pages are still read-only after the protection is removed, and are made writable
+    /// by the page fault handler.
+    pub(super) fn write_protect_userfault(
+        &self,
+        vm_space: &VmSpace,
+        range: &Range<Vaddr>,
+        write_protect: bool,
+    ) -> Result<()> {
+        let range = get_intersected_range(range, &self.range());
+        let mut cursor = vm_space.cursor_mut(&range)?;
+        let op = |p: &mut PageProperty| {
+            if write_protect {
+                p.flags -= PageFlags::W;
+                p.flags |= PAGE_FLAG_UFFD_WP;
+            } else {
+                p.flags -= PAGE_FLAG_UFFD_WP;
+            }
+        };
+        while cursor.virt_addr() < range.end {
+            if let Some(va) = cursor.protect_next(range.end - cursor.virt_addr(), op) {
+                cursor.flusher().issue_tlb_flush(TlbFlushOp::Range(va));
+            } else {
+                break;
+            }
+        }
+        cursor.flusher().dispatch_tlb_flush();
+
+        Ok(())
+    }
+
+    /// Returns the addresses of at most `max_pages` mapped pages that are at or after
+    /// `start_addr`.
+    pub(super) fn mapped_pages_from(
+        &self,
+        vm_space: &VmSpace,
+        start_addr: Vaddr,
+        max_pages: usize,
+    ) -> Result<Vec<Vaddr>> {
+        let range = max(start_addr, self.map_to_addr)..self.map_end();
+        if range.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let page_addrs = vm_space
+            .cursor(&range)?
+            .flat_map(|item| match item {
+                VmItem::Mapped { va, frame, .. } => base_pages_of(va, &frame, &range)
+                    .map(|(page_addr, _)| page_addr)
+                    .collect::<Vec<_>>(),
+                VmItem::NotMapped { .. } => Vec::new(),
+            })
+            .take(max_pages)
+            .collect();
+        Ok(page_addrs)
+    }
+
+    /// Replaces the page mapped at `page_addr` with the frame returned by `replace`.
+    ///
+    /// The page is kept if it has other references than the mapping (e.g., if it is pinned).
+    /// Otherwise, the page is write-protected before `replace` is called, so its contents cannot
+    /// change while `replace` inspects them. The new frame is mapped read-only, so that writes to it
+    /// trigger copy-on-write page faults. If `replace` returns `None`, the page is kept and will
+    /// be made writable again by the page fault handler.
+    pub(super) fn replace_page(
+        &self,
+        vm_space: &VmSpace,
+        page_addr: Vaddr,
+        replace: impl FnOnce(&UFrame) -> Option<UFrame>,
+    ) -> Result<()> {
+        let page_range = page_addr..page_addr + PAGE_SIZE;
+        let mut cursor = vm_space.cursor_mut(&page_range)?;
+        let VmItem::Mapped {
+            va,
+            frame,
+            mut prop,
+        } = cursor.query()?
+        else {
+            return Ok(());
+        };
+        // Besides the page table and `frame`, the page may be referenced by others (e.g., pinned
+        // for direct I/O, or shared with a forked process). Replacing it would orphan the
+        // references, so it is kept.
+        if frame.reference_count() != 2 {
+            return Ok(());
+        }
+
+        if prop.flags.contains(PageFlags::W) {
+            cursor.protect_next(PAGE_SIZE, |p| p.flags -= PageFlags::W);
+            cursor.flusher().issue_tlb_flush(TlbFlushOp::Address(va));
+            cursor.flusher().dispatch_tlb_flush();
+            cursor.jump(va)?;
+        }
+
+        let Some(new_frame) = replace(&frame) else {
+            return Ok(());
+        };
+        prop.flags -= PageFlags::W;
+        cursor.map(new_frame, prop);
+
+        Ok(())
+    }
+
+    /// Replaces the mapped pages within `range` that satisfy `is_shared_page` with private
+    /// copies.
+    ///
+    /// The copies are mapped read-only and are made writable by the page fault handler.
+    pub(super) fn copy_shared_pages(
+        &self,
+        vm_space: &VmSpace,
+        range: &Range<Vaddr>,
+        is_shared_page: impl Fn(&UFrame) -> bool,
+    ) -> Result<()> {
+        let range = get_intersected_range(range, &self.range());
+        let shared_pages: Vec<_> = vm_space
+            .cursor(&range)?
+            .filter_map(|item| match item {
+                VmItem::Mapped { va, frame, prop } if is_shared_page(&frame) => {
+                    Some((va, frame, prop))
+                }
+                _ => None,
+            })
+            .collect();
+
+        let mut cursor = vm_space.cursor_mut(&range)?;
+        for (va, frame, prop) in shared_pages {
+            let new_frame = duplicate_frame(&frame, self.memory_policy.as_deref())?;
+            cursor.jump(va)?;
+            cursor.map(new_frame.into(), prop);
+        }
+
+        Ok(())
+    }
+
+    /// Swaps out the page mapped at `page_addr`.
+    ///
+    /// Returns whether the page is swapped out. The page is kept if it is also mapped elsewhere
+    /// (e.g., shared with a forked process or merged by KSM). Unless `is_forced` is true, the
+    /// page is also kept if it has been accessed since the last call, in which case its accessed
+    /// bit is cleared so that it can be swapped out next time if it is not accessed again.
+    pub(super) fn swap_out_page(
+        &self,
+        vm_space: &VmSpace,
+        page_addr: Vaddr,
+        is_forced: bool,
+    ) -> Result<bool> {
+        debug_assert!(self.is_swappable());
+
+        // The base pages of a huge page are swapped out one by one.
+        self.split_huge_page(vm_space, page_addr)?;
+
+        let Some(slot) = swap::alloc_slot() else {
+            return_errno_with_message!(Errno::ENOSPC, "the swap areas are full");
+        };
+        let page_range = page_addr..page_addr + PAGE_SIZE;
+
+        // Write-protect the page, so that its contents cannot change while they are written to
+        // the swap area. The page table is not locked during the write, since it may sleep.
+        let frame = {
+            let mut cursor = vm_space.cursor_mut(&page_range)?;
+            let VmItem::Mapped { va, frame, prop } = cursor.query()? else {
+                return Ok(false);
+            };
+            // One reference is held by the page table, and the other is held by `frame`.
+            if is_zero_frame(&frame) || frame.reference_count() != 2 {
+                return Ok(false);
+            }
+
+            let mut flags_to_clear = PageFlags::W;
+            if !is_forced && prop.flags.contains(PageFlags::ACCESSED) {
+                flags_to_clear = PageFlags::ACCESSED;
+            }
+            if prop.flags.intersects(flags_to_clear) {
+                cursor.protect_next(PAGE_SIZE, |p| p.flags -= flags_to_clear);
+                cursor.flusher().issue_tlb_flush(TlbFlushOp::Address(va));
+                cursor.flusher().dispatch_tlb_flush();
+            }
+            if flags_to_clear == PageFlags::ACCESSED {
+                return Ok(false);
+            }
+
+            frame
+        };
+
+        slot.write(&mut frame.reader().to_fallible())?;
+
+        let mut cursor = vm_space.cursor_mut(&page_range)?;
+        let VmItem::Mapped {
+            frame: mapped_frame,
+            ..
+        } = cursor.query()?
+        else {
+            return Ok(false);
+        };
+        // If the page has been written (i.e., copied on write) or shared (e.g., by fork) during
+        // the write, the slot is dropped and the page is kept. Otherwise, the page table,
+        // `frame`, and `mapped_frame` hold the only references.
+        if mapped_frame.start_paddr() != frame.start_paddr() || frame.reference_count() != 3 {
+            return Ok(false);
+        }
+        self.swapped_pages.lock().insert(page_addr, Arc::new(slot));
+        cursor.unmap(PAGE_SIZE);
+
+        Ok(true)
+    }
+
+    /// Swaps in the page at `page_addr` if it is swapped out.
+    ///
+    /// Returns whether the page is swapped in.
+    fn swap_in_page(&self, vm_space: &VmSpace, page_addr: Vaddr) -> Result<bool> {
+        loop {
+            let Some(slot) = self.swapped_page(page_addr) else {
+                return Ok(false);
+            };
+
+            // The page table is not locked during the read, since it may sleep.
+            let frame = alloc_user_frame(
+                FrameAllocOptions::new().zeroed(false),
+                self.memory_policy.as_deref(),
+            )?;
+            slot.read(&mut frame.writer().to_fallible())?;
+
+            let mut cursor = vm_space.cursor_mut(&(page_addr..page_addr + PAGE_SIZE))?;
+            {
+                let mut swapped_pages = self.swapped_pages.lock();
+                // The page may have been swapped in, and even swapped out again, by others
+                // during the read.
+                if !swapped_pages
+                    .get(&page_addr)
+                    .is_some_and(|current| Arc::ptr_eq(current, &slot))
+                {
+                    continue;
+                }
+                swapped_pages.remove(&page_addr);
+            }
+
+            // The page is only mapped here, so it can be writable if the mapping is writable.
+            let page_flags = PageFlags::from(self.perms) | PageFlags::ACCESSED | PageFlags::DIRTY;
+            cursor.map(
+                frame.into(),
+                PageProperty::new(page_flags, CachePolicy::Writeback),
+            );
+
+            return Ok(true);
+        }
+    }
+
+    /// Swaps in all the pages of the mapping that are stored in `area`.
+    pub(super) fn swap_in_area(&self, vm_space: &VmSpace, area: &Arc<SwapArea>) -> Result<()> {
+        let page_addrs: Vec<Vaddr> = self
+            .swapped_pages
+            .lock()
+            .iter()
+            .filter(|(_, slot)| Arc::ptr_eq(slot.area(), area))
+            .map(|(page_addr, _)| *page_addr)
+            .collect();
+
+        for page_addr in page_addrs {
+            self.swap_in_page(vm_space, page_addr)?;
+        }
+
+        Ok(())
+    }
+
     /// Change the perms of the mapping.
     pub(super) fn protect(self, vm_space: &VmSpace, perms: VmPerms) -> Self {
         let range = self.range();
 
         let mut cursor = vm_space.cursor_mut(&range).unwrap();
 
-        let op = |p: &mut PageProperty| p.flags = perms.into();
+        // Pages that are not writable may be shared copy-on-write (e.g., the zero frame or
+        // the pages shared with a forked process), so the write permission is left to be
+        // granted by the page fault handler.
+        let op = |p: &mut PageProperty| {
+            let mut flags: PageFlags = perms.into();
+            if !p.flags.contains(PageFlags::W) {
+                flags -= PageFlags::W;
+            }
+            flags |= p.flags & PAGE_FLAG_UFFD_WP;
+            p.flags = flags;
+        };
         while cursor.virt_addr() < range.end {
             if let Some(va) = cursor.protect_next(range.end - cursor.virt_addr(), op) {
                 cursor.flusher().issue_tlb_flush(TlbFlushOp::Range(va));
@@ -409,6 +1599,40 @@ impl VmMapping {
     }
 }
 
+/// Prepares a page for a private anonymous mapping.
+///
+/// A read access maps the shared zero frame read-only, so that no physical memory is consumed
+/// until the page is written. A write access allocates a new zeroed frame directly according to
+/// the memory `policy`.
+fn prepare_anonymous_page(write: bool, policy: Option<&MemoryPolicy>) -> Result<(UFrame, bool)> {
+    if write {
+        Ok((
+            alloc_user_frame(&FrameAllocOptions::new(), policy)?.into(),
+            false,
+        ))
+    } else {
+        Ok((zero_frame()?, true))
+    }
+}
+
+/// Returns the addresses and the physical addresses of the base pages within `range` that are
+/// backed by `frame`, which is mapped at `va`.
+///
+/// If `frame` is a huge frame, `va` may not be aligned to its size if it is the start of `range`.
+fn base_pages_of(
+    va: Vaddr,
+    frame: &UFrame,
+    range: &Range<Vaddr>,
+) -> impl Iterator<Item = (Vaddr, Paddr)> {
+    let frame_start = va.align_down(frame.size());
+    let frame_end = frame_start + frame.size();
+    let start_paddr = frame.start_paddr();
+
+    (max(frame_start, range.start)..min(frame_end, range.end))
+        .step_by(PAGE_SIZE)
+        .map(move |page_addr| (page_addr, start_paddr + (page_addr - frame_start)))
+}
+
 /// A wrapper that represents a mapped [`Vmo`] and provide required functionalities
 /// that need to be provided to mappings from the VMO.
 #[derive(Debug)]
@@ -438,6 +1662,19 @@ impl MappedVmo {
         self.vmo.commit_page(self.range.start + page_offset)
     }
 
+    /// Marks the page at the input offset in the mapped VMO as dirty.
+    fn mark_page_dirty(&self, page_offset: usize) -> Result<()> {
+        debug_assert!(page_offset < self.range.len());
+        self.vmo.mark_page_dirty(self.range.start + page_offset)
+    }
+
+    /// Writes the dirty pages within the range (relative to the start of the mapped range)
+    /// back.
+    fn write_back(&self, range: &Range<usize>) -> Result<()> {
+        let range = self.range.start + range.start..self.range.start + range.end;
+        self.vmo.write_back(range)
+    }
+
     /// Traverses the indices within a specified range of a VMO sequentially.
     ///
     /// For each index position, you have the option to commit the page as well as
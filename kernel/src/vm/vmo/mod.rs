@@ -14,7 +14,7 @@ use ostd::{
     mm::{FrameAllocOptions, UFrame, UntypedMem, VmReader, VmWriter},
 };
 
-use crate::prelude::*;
+use crate::{prelude::*, vm::memcg::alloc_user_frame};
 
 mod dyn_cap;
 mod options;
@@ -204,7 +204,7 @@ impl Vmo_ {
     /// Prepares a new `UFrame` for the target index in pages, returns this new frame.
     fn prepare_page(&self, page_idx: usize) -> Result<UFrame> {
         match &self.pager {
-            None => Ok(FrameAllocOptions::new().alloc_frame()?.into()),
+            None => Ok(alloc_user_frame(&FrameAllocOptions::new(), None)?.into()),
             Some(pager) => pager.commit_page(page_idx),
         }
     }
@@ -214,7 +214,7 @@ impl Vmo_ {
         if let Some(pager) = &self.pager {
             pager.commit_overwrite(page_idx)
         } else {
-            Ok(FrameAllocOptions::new().alloc_frame()?.into())
+            Ok(alloc_user_frame(&FrameAllocOptions::new(), None)?.into())
         }
     }
 
@@ -415,6 +415,20 @@ impl Vmo_ {
         Ok(())
     }
 
+    fn mark_page_dirty(&self, offset: usize) -> Result<()> {
+        if let Some(pager) = &self.pager {
+            pager.update_page(offset / PAGE_SIZE)?;
+        }
+        Ok(())
+    }
+
+    fn write_back(&self, range: Range<usize>) -> Result<()> {
+        if let Some(pager) = &self.pager {
+            pager.write_back(get_page_idx_range(&range))?;
+        }
+        Ok(())
+    }
+
     /// Determines whether a page is committed.
     pub fn is_page_committed(&self, page_idx: usize) -> bool {
         self.pages
@@ -447,6 +461,27 @@ impl<R> Vmo<R> {
     pub fn flags(&self) -> VmoFlags {
         self.0.flags()
     }
+
+    /// Returns whether the two capabilities refer to the same VMO.
+    pub fn is_same<R2>(&self, other: &Vmo<R2>) -> bool {
+        Arc::ptr_eq(&self.0, &other.0)
+    }
+
+    /// Returns the number of the capabilities of the VMO, including the ones held by the
+    /// mappings of the VMO.
+    pub fn num_capabilities(&self) -> usize {
+        Arc::strong_count(&self.0)
+    }
+
+    /// Marks the page at the offset as dirty after it is written via a mapping.
+    pub(in crate::vm) fn mark_page_dirty(&self, offset: usize) -> Result<()> {
+        self.0.mark_page_dirty(offset)
+    }
+
+    /// Writes the dirty pages within the range (in bytes) back to the pager.
+    pub(in crate::vm) fn write_back(&self, range: Range<usize>) -> Result<()> {
+        self.0.write_back(range)
+    }
 }
 
 /// Gets the page index range that contains the offset range of VMO.
@@ -1,5 +1,7 @@
 // SPDX-License-Identifier: MPL-2.0
 
+use core::ops::Range;
+
 use ostd::mm::UFrame;
 
 use crate::prelude::*;
@@ -55,4 +57,8 @@ pub trait Pager: Send + Sync {
     /// Notify the pager that the frame will be fully overwritten soon, so pager can
     /// choose not to initialize it.
     fn commit_overwrite(&self, idx: usize) -> Result<UFrame>;
+
+    /// Ask the pager to write the dirty frames within the specified range of indices back
+    /// (e.g., to the disk).
+    fn write_back(&self, idx_range: Range<usize>) -> Result<()>;
 }
@@ -0,0 +1,109 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! The write-xor-execute (W^X) policy of user mappings.
+//!
+//! A mapping that is writable and executable at the same time allows the code to be modified
+//! and executed without any further system call, which makes code injection much easier. Like
+//! the `execmem` checks of the Linux security modules, the policy is controlled by
+//! `/proc/sys/vm/wx_policy`:
+//! - `0`: The writable and executable mappings are allowed.
+//! - `1`: The writable and executable mappings are allowed, but they are logged.
+//! - `2`: The writable and executable mappings are denied with `EACCES` and logged. This is
+//!   the default.
+//!
+//! A process can opt out of the policy by setting [`Personality::READ_IMPLIES_EXEC`], e.g.,
+//! with `setarch -X`, since the legacy programs that require it expect all the readable
+//! mappings to be executable.
+//!
+//! Just-in-time compilers are still supported by writing the code to a writable mapping and
+//! then making it executable with `mprotect`. Such transitions are logged for auditing, and
+//! the instruction caches are synchronized so that the new code is visible to all CPUs.
+
+use core::{
+    ops::Range,
+    sync::atomic::{AtomicU8, Ordering},
+};
+
+use crate::{prelude::*, process::personality::Personality, vm::perms::VmPerms};
+
+/// The maximum value of the policy.
+pub const MAX_WX_POLICY: u8 = 2;
+
+static WX_POLICY: AtomicU8 = AtomicU8::new(MAX_WX_POLICY);
+
+/// Returns the current policy.
+pub fn wx_policy() -> u8 {
+    WX_POLICY.load(Ordering::Relaxed)
+}
+
+/// Sets the policy.
+pub fn set_wx_policy(value: u8) -> Result<()> {
+    if value > MAX_WX_POLICY {
+        return_errno_with_message!(Errno::EINVAL, "the policy is invalid");
+    }
+    WX_POLICY.store(value, Ordering::Relaxed);
+    Ok(())
+}
+
+/// Adds [`VmPerms::EXEC`] to the readable permissions if the current process has
+/// [`Personality::READ_IMPLIES_EXEC`].
+pub fn apply_personality(perms: VmPerms, ctx: &Context) -> VmPerms {
+    if perms.contains(VmPerms::READ) && is_exempted(ctx) {
+        perms | VmPerms::EXEC
+    } else {
+        perms
+    }
+}
+
+/// Checks whether the current process can map the range with the permissions.
+///
+/// `op` is the name of the operation, which is used in the log.
+pub fn check_wx(op: &str, range: &Range<Vaddr>, perms: VmPerms, ctx: &Context) -> Result<()> {
+    if !perms.contains(VmPerms::WRITE | VmPerms::EXEC) || is_exempted(ctx) {
+        return Ok(());
+    }
+
+    let policy = wx_policy();
+    if policy == 0 {
+        return Ok(());
+    }
+
+    warn!(
+        "W^X: {} of {:#x}..{:#x} with {:?} by process {} ({}) is {}",
+        op,
+        range.start,
+        range.end,
+        perms,
+        ctx.process.pid(),
+        ctx.process.executable_path(),
+        if policy >= 2 { "denied" } else { "allowed" },
+    );
+    if policy >= 2 {
+        return_errno_with_message!(
+            Errno::EACCES,
+            "the mapping cannot be writable and executable at the same time"
+        );
+    }
+
+    Ok(())
+}
+
+/// Logs that the range of the current process becomes executable at runtime.
+///
+/// It should be called only for the memory that may hold the code generated at runtime, e.g.,
+/// anonymous mappings or the mappings that are made executable by `mprotect`.
+pub fn audit_execmem(op: &str, range: &Range<Vaddr>, ctx: &Context) {
+    info!(
+        "execmem: {} of {:#x}..{:#x} by process {} ({})",
+        op,
+        range.start,
+        range.end,
+        ctx.process.pid(),
+        ctx.process.executable_path(),
+    );
+}
+
+fn is_exempted(ctx: &Context) -> bool {
+    Personality::from_bits_truncate(ctx.process.personality())
+        .contains(Personality::READ_IMPLIES_EXEC)
+}
@@ -38,7 +38,7 @@ impl Arch {
     /// Get the target triple for the architecture.
     pub fn triple(&self) -> &'static str {
         match self {
-            Arch::Aarch64 => "aarch64-unknown-none",
+            Arch::Aarch64 => "aarch64-unknown-none-softfloat",
             Arch::RiscV64 => "riscv64gc-unknown-none-elf",
             Arch::X86_64 => "x86_64-unknown-none",
         }
@@ -0,0 +1,263 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! Embeds the relocation table into the kernel ELF.
+//!
+//! On x86-64, the kernel image is linked at a fixed virtual address, but mapped at the address
+//! plus a random offset during early boot (i.e., KASLR). To make this work, the kernel is linked
+//! with `--emit-relocs`, and the linker script of the base crate reserves the `.krelocs` section.
+//! After the kernel is linked, the relocations that depend on the virtual address of the kernel
+//! image are written into the section, so that the boot code can apply them with the offset.
+//!
+//! A relocation depends on the virtual address of the kernel image if the address that it
+//! refers to (for the absolute ones), or either but not both of the address that it refers to
+//! and the address where it is applied (for the PC-relative ones), is in the kernel image, which
+//! starts at `KERNEL_VMA`. The boot sections are linked and loaded at the physical addresses, so
+//! their addresses do not depend on the offset.
+//!
+//! The layout of the table must match the one parsed in `ostd/src/arch/x86/boot/bsp_boot.S`:
+//! - The magic number `KREL`;
+//! - The numbers of the 64-bit locations to which the offset is added, the 32-bit locations to
+//!   which the offset is added, and the 32-bit locations from which the offset is subtracted, as
+//!   three `u32`s;
+//! - The physical addresses of the locations in the above order, as `u32`s.
+//!
+//! All the integers are little-endian.
+
+use std::{
+    fs::OpenOptions,
+    io::{Read, Seek, SeekFrom, Write},
+    ops::Range,
+    path::Path,
+};
+
+use super::ksymtab::find_section;
+
+const SECTION_NAME: &str = ".krelocs";
+const MAGIC: &[u8; 4] = b"KREL";
+
+/// The virtual address where the kernel image starts.
+///
+/// This must match `KERNEL_VMA` in the linker script of the base crate.
+const KERNEL_VMA: u64 = 0xffff_ffff_8000_0000;
+
+const SHT_RELA: u32 = 4;
+const SHF_ALLOC: u64 = 0x2;
+const PT_LOAD: u32 = 1;
+
+const R_X86_64_NONE: u32 = 0;
+const R_X86_64_64: u32 = 1;
+const R_X86_64_PC32: u32 = 2;
+const R_X86_64_PLT32: u32 = 4;
+const R_X86_64_32: u32 = 10;
+const R_X86_64_32S: u32 = 11;
+
+/// The relocations that depend on the virtual address of the kernel image.
+#[derive(Default)]
+struct Relocs {
+    add64: Vec<u32>,
+    add32: Vec<u32>,
+    sub32: Vec<u32>,
+}
+
+/// Writes the relocation table into the `.krelocs` section of the kernel ELF.
+///
+/// The kernel ELF is left unchanged if it has no such section. If the relocations cannot be
+/// read or the table does not fit in the section, the section is left empty, in which case the
+/// kernel image is not randomized.
+pub fn embed_relocation_table(elf_path: &Path) {
+    let mut file = OpenOptions::new()
+        .read(true)
+        .write(true)
+        .open(elf_path)
+        .unwrap();
+    let mut elf = Vec::new();
+    file.read_to_end(&mut elf).unwrap();
+    let Some((offset, size)) = find_section(&elf, SECTION_NAME) else {
+        return;
+    };
+
+    let table = match read_relocs(&elf) {
+        Ok(relocs) => encode_table(&relocs),
+        Err(err) => {
+            warn!("Failed to read the kernel relocations: {}", err);
+            Vec::new()
+        }
+    };
+    let table = if table.len() > size {
+        warn!(
+            "The kernel relocation table ({} bytes) does not fit in the `{}` section ({} bytes)",
+            table.len(),
+            SECTION_NAME,
+            size
+        );
+        Vec::new()
+    } else {
+        table
+    };
+    // Clear the magic number if there is no table, so that the kernel image is not randomized.
+    let table = if table.is_empty() {
+        vec![0; MAGIC.len()]
+    } else {
+        table
+    };
+
+    // Do not touch the ELF if the table is unchanged, so that the modified time of the ELF still
+    // tells whether the kernel is rebuilt.
+    if elf.get(offset..offset + table.len()) == Some(&table[..]) {
+        return;
+    }
+    file.seek(SeekFrom::Start(offset as u64)).unwrap();
+    file.write_all(&table).unwrap();
+}
+
+fn encode_table(relocs: &Relocs) -> Vec<u8> {
+    let mut table = Vec::new();
+    table.extend_from_slice(MAGIC);
+    for list in [&relocs.add64, &relocs.add32, &relocs.sub32] {
+        table.extend_from_slice(&(list.len() as u32).to_le_bytes());
+    }
+    for list in [&relocs.add64, &relocs.add32, &relocs.sub32] {
+        for paddr in list {
+            table.extend_from_slice(&paddr.to_le_bytes());
+        }
+    }
+    table
+}
+
+/// Reads the relocations that depend on the virtual address of the kernel image.
+fn read_relocs(elf: &[u8]) -> Result<Relocs, String> {
+    let elf = Elf(elf);
+    let segments = elf.load_segments()?;
+    let sections = elf.sections()?;
+
+    let mut relocs = Relocs::default();
+    for rela in sections.iter().filter(|section| section.typ == SHT_RELA) {
+        // Skip the relocations in the sections that are not loaded, e.g., the debug sections.
+        let target = sections
+            .get(rela.info as usize)
+            .ok_or("invalid target section")?;
+        if target.flags & SHF_ALLOC == 0 {
+            continue;
+        }
+        let symtab = sections
+            .get(rela.link as usize)
+            .ok_or("invalid symbol table")?;
+
+        for index in 0..rela.size / 24 {
+            let entry = rela.offset + index * 24;
+            let offset = elf.read(entry, 8)?;
+            let info = elf.read(entry + 8, 8)?;
+            let addend = elf.read(entry + 16, 8)?;
+            let (sym, typ) = ((info >> 32) as usize, info as u32);
+            let value = elf.read(symtab.offset + sym * 24 + 8, 8)?;
+
+            let list = match typ {
+                R_X86_64_NONE => None,
+                R_X86_64_64 => is_in_image(value.wrapping_add(addend)).then_some(&mut relocs.add64),
+                R_X86_64_32 | R_X86_64_32S => {
+                    is_in_image(value.wrapping_add(addend)).then_some(&mut relocs.add32)
+                }
+                R_X86_64_PC32 | R_X86_64_PLT32 => match (is_in_image(value), is_in_image(offset)) {
+                    (true, false) => Some(&mut relocs.add32),
+                    (false, true) => Some(&mut relocs.sub32),
+                    _ => None,
+                },
+                _ if is_in_image(value) || is_in_image(offset) => {
+                    return Err(format!(
+                        "unsupported relocation type {} at {:#x}",
+                        typ, offset
+                    ));
+                }
+                _ => None,
+            };
+            let Some(list) = list else {
+                continue;
+            };
+
+            let paddr = segments
+                .iter()
+                .find(|segment| segment.vaddr.contains(&offset))
+                .map(|segment| offset - segment.vaddr.start + segment.paddr)
+                .ok_or_else(|| format!("relocation at {:#x} is not loaded", offset))?;
+            list.push(u32::try_from(paddr).map_err(|_| "relocation is not in the low 4 GiB")?);
+        }
+    }
+
+    Ok(relocs)
+}
+
+fn is_in_image(addr: u64) -> bool {
+    addr >= KERNEL_VMA
+}
+
+/// A 64-bit little-endian ELF.
+struct Elf<'a>(&'a [u8]);
+
+struct Section {
+    typ: u32,
+    flags: u64,
+    offset: usize,
+    size: usize,
+    link: u32,
+    info: u32,
+}
+
+struct LoadSegment {
+    vaddr: Range<u64>,
+    paddr: u64,
+}
+
+impl Elf<'_> {
+    fn read(&self, offset: usize, len: usize) -> Result<u64, String> {
+        let bytes = offset
+            .checked_add(len)
+            .and_then(|end| self.0.get(offset..end))
+            .ok_or("truncated ELF")?;
+        Ok(bytes
+            .iter()
+            .rev()
+            .fold(0, |value, byte| value << 8 | *byte as u64))
+    }
+
+    fn sections(&self) -> Result<Vec<Section>, String> {
+        let section_headers = self.read(0x28, 8)? as usize;
+        let header_size = self.read(0x3a, 2)? as usize;
+        let num_sections = self.read(0x3c, 2)? as usize;
+
+        (0..num_sections)
+            .map(|index| {
+                let header = section_headers + index * header_size;
+                Ok(Section {
+                    typ: self.read(header + 0x4, 4)? as u32,
+                    flags: self.read(header + 0x8, 8)?,
+                    offset: self.read(header + 0x18, 8)? as usize,
+                    size: self.read(header + 0x20, 8)? as usize,
+                    link: self.read(header + 0x28, 4)? as u32,
+                    info: self.read(header + 0x2c, 4)? as u32,
+                })
+            })
+            .collect()
+    }
+
+    fn load_segments(&self) -> Result<Vec<LoadSegment>, String> {
+        let program_headers = self.read(0x20, 8)? as usize;
+        let header_size = self.read(0x36, 2)? as usize;
+        let num_segments = self.read(0x38, 2)? as usize;
+
+        let mut segments = Vec::new();
+        for index in 0..num_segments {
+            let header = program_headers + index * header_size;
+            if self.read(header, 4)? as u32 != PT_LOAD {
+                continue;
+            }
+            let vaddr = self.read(header + 0x10, 8)?;
+            let paddr = self.read(header + 0x18, 8)?;
+            let file_size = self.read(header + 0x20, 8)?;
+            segments.push(LoadSegment {
+                vaddr: vaddr..vaddr + file_size,
+                paddr,
+            });
+        }
+        Ok(segments)
+    }
+}
@@ -0,0 +1,146 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! Embeds the symbol table into the kernel ELF.
+//!
+//! The linker script of the base crate reserves the `.ksymtab` section. After the kernel is
+//! linked, the addresses and the demangled names of its functions are written into the section,
+//! so that the kernel can symbolize the stack traces when it panics.
+//!
+//! The layout of the table must match the one parsed in `ostd/src/panic/symbols.rs`:
+//! - The magic number `KSYM` and the number of symbols as a `u32`;
+//! - The symbols sorted by their addresses, each of which is the address as a `u64`, and the
+//!   offset and the length of its name as two `u32`s;
+//! - The names, where the offsets of the names start.
+//!
+//! All the integers are little-endian.
+
+use std::{
+    fs::OpenOptions,
+    io::{Read, Seek, SeekFrom, Write},
+    path::Path,
+    process::Command,
+};
+
+const SECTION_NAME: &str = ".ksymtab";
+const MAGIC: &[u8; 4] = b"KSYM";
+
+/// Writes the symbol table into the `.ksymtab` section of the kernel ELF.
+///
+/// The kernel ELF is left unchanged if it has no such section, if the symbols cannot be read, or
+/// if the table does not fit in the section.
+pub fn embed_symbol_table(elf_path: &Path) {
+    let mut file = OpenOptions::new()
+        .read(true)
+        .write(true)
+        .open(elf_path)
+        .unwrap();
+    let mut elf = Vec::new();
+    file.read_to_end(&mut elf).unwrap();
+    let Some((offset, size)) = find_section(&elf, SECTION_NAME) else {
+        return;
+    };
+
+    let Some(symbols) = read_function_symbols(elf_path) else {
+        warn!("Failed to read the kernel symbols with `nm`");
+        return;
+    };
+    let table = encode_table(&symbols);
+    if table.len() > size {
+        warn!(
+            "The kernel symbol table ({} bytes) does not fit in the `{}` section ({} bytes)",
+            table.len(),
+            SECTION_NAME,
+            size
+        );
+        return;
+    }
+
+    // Do not touch the ELF if the table is unchanged, so that the modified time of the ELF still
+    // tells whether the kernel is rebuilt.
+    if elf.get(offset..offset + table.len()) == Some(&table[..]) {
+        return;
+    }
+    file.seek(SeekFrom::Start(offset as u64)).unwrap();
+    file.write_all(&table).unwrap();
+}
+
+/// Reads the addresses and the demangled names of the functions, sorted by their addresses.
+fn read_function_symbols(elf_path: &Path) -> Option<Vec<(u64, String)>> {
+    let output = Command::new("nm")
+        .arg("--defined-only")
+        .arg("--demangle")
+        .arg(elf_path)
+        .output()
+        .ok()?;
+    if !output.status.success() {
+        return None;
+    }
+
+    let mut symbols: Vec<(u64, String)> = String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .filter_map(|line| {
+            // Each line is `<address> <type> <name>`, and the name may contain spaces.
+            let mut fields = line.splitn(3, ' ');
+            let addr = u64::from_str_radix(fields.next()?, 16).ok()?;
+            let typ = fields.next()?;
+            let name = fields.next()?;
+            matches!(typ, "T" | "t" | "W" | "w").then(|| (addr, name.to_string()))
+        })
+        .collect();
+    symbols.sort_by_key(|(addr, _)| *addr);
+    symbols.dedup_by_key(|(addr, _)| *addr);
+    Some(symbols)
+}
+
+fn encode_table(symbols: &[(u64, String)]) -> Vec<u8> {
+    let mut table = Vec::new();
+    table.extend_from_slice(MAGIC);
+    table.extend_from_slice(&(symbols.len() as u32).to_le_bytes());
+
+    let mut names = Vec::new();
+    for (addr, name) in symbols {
+        table.extend_from_slice(&addr.to_le_bytes());
+        table.extend_from_slice(&(names.len() as u32).to_le_bytes());
+        table.extend_from_slice(&(name.len() as u32).to_le_bytes());
+        names.extend_from_slice(name.as_bytes());
+    }
+    table.extend_from_slice(&names);
+    table
+}
+
+/// Finds the section with the name in a 64-bit little-endian ELF, returning the file offset
+/// and the size of the section.
+pub(super) fn find_section(elf: &[u8], name: &str) -> Option<(usize, usize)> {
+    let read = |offset: usize, len: usize| -> Option<u64> {
+        let bytes = elf.get(offset..offset.checked_add(len)?)?;
+        Some(
+            bytes
+                .iter()
+                .rev()
+                .fold(0, |value, byte| value << 8 | *byte as u64),
+        )
+    };
+
+    const ELFCLASS64: u8 = 2;
+    const ELFDATA2LSB: u8 = 1;
+    if elf.get(..6)? != [0x7f, b'E', b'L', b'F', ELFCLASS64, ELFDATA2LSB] {
+        return None;
+    }
+    let section_headers = read(0x28, 8)? as usize;
+    let header_size = read(0x3a, 2)? as usize;
+    let num_sections = read(0x3c, 2)? as usize;
+    let names_index = read(0x3e, 2)? as usize;
+    let header = |index: usize| section_headers + index * header_size;
+    let names = read(header(names_index) + 0x18, 8)? as usize;
+
+    (0..num_sections).find_map(|index| {
+        let name_offset = names + read(header(index), 4)? as usize;
+        let name_bytes = elf.get(name_offset..name_offset + name.len() + 1)?;
+        if name_bytes[..name.len()] != *name.as_bytes() || name_bytes[name.len()] != 0 {
+            return None;
+        }
+        let offset = read(header(index) + 0x18, 8)? as usize;
+        let size = read(header(index) + 0x20, 8)? as usize;
+        Some((offset, size))
+    })
+}
@@ -2,6 +2,8 @@
 
 mod bin;
 mod grub;
+mod krelocs;
+mod ksymtab;
 mod qcow2;
 
 use std::{
@@ -211,6 +213,9 @@ fn build_kernel_elf(
         // or exception handlers may overwrite kernel data in the red zone. Therefore, we disable
         // this optimization.
         "-C no-redzone=y",
+        // Frame pointers let the sampling profiler unwind the kernel stack in the interrupt
+        // context, where the DWARF-based unwinder cannot be used.
+        "-C force-frame-pointers=yes",
     ]);
 
     if matches!(arch, Arch::X86_64) {
@@ -218,6 +223,9 @@ fn build_kernel_elf(
         // It makes running on Intel CPUs after Ivy Bridge (2012) faster, but much slower
         // on older CPUs.
         rustflags.push("-C target-feature=+ermsb");
+        // The relocations are kept in the kernel ELF, so that the kernel image can be relocated
+        // to a random virtual address at boot time. See `krelocs.rs`.
+        rustflags.push("-C link-arg=--emit-relocs");
     }
 
     let mut command = cargo();
@@ -252,6 +260,8 @@ fn build_kernel_elf(
         .join(&target_os_string)
         .join(profile_name_adapter(profile))
         .join(get_current_crate_info().name);
+    ksymtab::embed_symbol_table(&aster_bin_path);
+    krelocs::embed_relocation_table(&aster_bin_path);
 
     AsterBin::new(
         aster_bin_path,
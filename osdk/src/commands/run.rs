@@ -65,6 +65,10 @@ fn adapt_for_gdb_server(config: &mut Config, gdb_server_str: &str) -> Option<Vsc
     };
     config.run.qemu.args += &qemu_gdb_args;
 
+    // The debugger sees the addresses that the kernel is linked at, so the kernel image should
+    // not be relocated to a random address.
+    config.run.boot.kcmdline.insert(0, "nokaslr".to_owned());
+
     if gdb_server_args.wait_client {
         config.run.qemu.args += " -S";
     }
@@ -0,0 +1,102 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! Multiprocessor Boot Support
+//!
+//! The CPUs other than the boot CPU are turned on by the PSCI firmware with the
+//! `CPU_ON` call. A CPU that is turned on jumps to `_start_ap` in the physical
+//! address space, with the context ID, which is the CPU ID assigned by the
+//! kernel, in `x0`.
+//!
+//! The CPUs are identified by the affinity fields of their `MPIDR_EL1`, which
+//! are the `reg` properties of the CPU nodes in the device tree. The boot CPU is
+//! always CPU 0, and the other CPUs are numbered in the order that they appear
+//! in the device tree.
+
+use alloc::vec::Vec;
+
+use spin::Once;
+
+use super::{super::psci, DEVICE_TREE};
+use crate::{
+    cpu::CpuId,
+    mm::{kspace::kernel_loaded_offset, paddr_to_vaddr},
+};
+
+/// The affinity fields (Aff3, Aff2, Aff1 and Aff0) of `MPIDR_EL1`.
+const MPIDR_AFFINITY_MASK: u64 = 0xff_00ff_ffff;
+
+/// The affinity values of the CPUs, indexed by the CPU IDs.
+static MPIDRS: Once<Vec<u64>> = Once::new();
+
+fn mpidrs() -> &'static [u64] {
+    MPIDRS.call_once(|| {
+        let boot_mpidr = current_mpidr();
+
+        let mut mpidrs = Vec::new();
+        mpidrs.push(boot_mpidr);
+        for cpu in DEVICE_TREE.get().unwrap().cpus() {
+            let is_disabled = cpu
+                .property("status")
+                .and_then(|status| status.as_str())
+                .is_some_and(|status| status != "okay");
+            let mpidr = cpu.ids().first() as u64 & MPIDR_AFFINITY_MASK;
+            if !is_disabled && mpidr != boot_mpidr {
+                mpidrs.push(mpidr);
+            }
+        }
+        mpidrs
+    })
+}
+
+/// Returns the affinity value of the current CPU.
+pub(crate) fn current_mpidr() -> u64 {
+    let mpidr: u64;
+    // SAFETY: Reading `MPIDR_EL1` has no side effects.
+    unsafe { core::arch::asm!("mrs {}, mpidr_el1", out(reg) mpidr, options(nomem, nostack)) };
+    mpidr & MPIDR_AFFINITY_MASK
+}
+
+/// Returns the affinity value of a CPU.
+pub(crate) fn mpidr_of(cpu_id: CpuId) -> u64 {
+    mpidrs()[cpu_id.as_usize()]
+}
+
+/// Get the number of processors
+pub(crate) fn get_num_processors() -> Option<u32> {
+    Some(mpidrs().len() as u32)
+}
+
+/// Brings up all application processors.
+pub(crate) fn bringup_all_aps() {
+    fill_boot_stack_array_ptr();
+
+    extern "C" {
+        fn _start_ap();
+    }
+    let start_paddr = _start_ap as usize - kernel_loaded_offset();
+
+    for (cpu_id, mpidr) in mpidrs().iter().enumerate().skip(1) {
+        if let Err(err) = psci::cpu_on(*mpidr, start_paddr, cpu_id) {
+            log::warn!("Failed to turn on CPU {:#x}: {:?}", mpidr, err);
+        }
+    }
+}
+
+/// Initializes the boot stack array in the AP boot code with the given pages.
+fn fill_boot_stack_array_ptr() {
+    let pages = &crate::boot::smp::AP_BOOT_INFO
+        .get()
+        .unwrap()
+        .boot_stack_array;
+
+    extern "C" {
+        static __ap_boot_stack_array_pointer: u64;
+    }
+
+    // SAFETY: This pointer points to a static variable defined in the `boot.S`.
+    let ptr = unsafe { &__ap_boot_stack_array_pointer as *const u64 as *mut u64 };
+    // SAFETY: We only write to it once.
+    unsafe {
+        ptr.write_volatile(paddr_to_vaddr(pages.start_paddr()) as u64);
+    }
+}
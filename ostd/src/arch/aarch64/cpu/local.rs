@@ -0,0 +1,26 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! Architecture dependent CPU-local information utilities.
+//!
+//! The base address of the CPU-local storage is kept in `TPIDR_EL1`, which is
+//! not accessible to the user space.
+
+pub(crate) unsafe fn set_base(addr: u64) {
+    core::arch::asm!(
+        "msr tpidr_el1, {addr}",
+        addr = in(reg) addr,
+        options(preserves_flags, nostack)
+    );
+}
+
+pub(crate) fn get_base() -> u64 {
+    let mut base;
+    unsafe {
+        core::arch::asm!(
+            "mrs {base}, tpidr_el1",
+            base = out(reg) base,
+            options(preserves_flags, nostack)
+        );
+    }
+    base
+}
@@ -0,0 +1,465 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! CPU
+
+pub mod local;
+
+use alloc::boxed::Box;
+use core::{
+    arch::asm,
+    sync::atomic::{AtomicBool, Ordering::Relaxed},
+};
+
+use log::debug;
+
+pub use super::trap::GeneralRegs as RawGeneralRegs;
+use super::trap::{
+    handle_irq, read_esr_and_far, TrapFrame, TrapKind, UserContext as RawUserContext,
+};
+use crate::{
+    task::scheduler,
+    user::{ReturnReason, UserContextApi, UserContextApiInternal},
+};
+
+/// The condition flags (`N`, `Z`, `C` and `V`) of `SPSR_EL1`.
+///
+/// The other bits are cleared before returning to the user space, so that the user
+/// space runs at EL0 with all the exceptions unmasked.
+const SPSR_CONDITION_FLAGS: usize = 0xf000_0000;
+
+/// The bit offset of the exception class in `ESR_EL1`.
+const ESR_EC_SHIFT: usize = 26;
+
+/// Cpu context, including both general-purpose registers and FPU state.
+#[derive(Clone, Default, Debug)]
+#[repr(C)]
+pub struct UserContext {
+    user_context: RawUserContext,
+    fpu_state: FpuState,
+    cpu_exception_info: CpuExceptionInfo,
+}
+
+/// CPU exception information.
+#[derive(Clone, Copy, Debug, Default)]
+#[repr(C)]
+pub struct CpuExceptionInfo {
+    /// The type of the exception.
+    pub code: CpuException,
+    /// The virtual address where a page fault occurred.
+    pub page_fault_addr: usize,
+    /// The syndrome of the exception (`ESR_EL1`).
+    pub error_code: usize,
+}
+
+impl CpuExceptionInfo {
+    /// Get corresponding CPU exception
+    pub fn cpu_exception(&self) -> CpuException {
+        self.code
+    }
+}
+
+impl UserContext {
+    /// Returns a reference to the general registers.
+    pub fn general_regs(&self) -> &RawGeneralRegs {
+        &self.user_context.general
+    }
+
+    /// Returns a mutable reference to the general registers
+    pub fn general_regs_mut(&mut self) -> &mut RawGeneralRegs {
+        &mut self.user_context.general
+    }
+
+    /// Returns the trap information.
+    pub fn trap_information(&self) -> &CpuExceptionInfo {
+        &self.cpu_exception_info
+    }
+
+    /// Returns a reference to the FPU state.
+    pub fn fpu_state(&self) -> &FpuState {
+        &self.fpu_state
+    }
+
+    /// Returns a mutable reference to the FPU state.
+    pub fn fpu_state_mut(&mut self) -> &mut FpuState {
+        &mut self.fpu_state
+    }
+
+    /// Sets thread-local storage pointer.
+    pub fn set_tls_pointer(&mut self, tls: usize) {
+        self.set_tpidr(tls)
+    }
+
+    /// Gets thread-local storage pointer.
+    pub fn tls_pointer(&self) -> usize {
+        self.tpidr()
+    }
+
+    /// Gets the value of the thread ID register (`TPIDR_EL0`).
+    pub fn tpidr(&self) -> usize {
+        self.user_context.tpidr
+    }
+
+    /// Sets the value of the thread ID register (`TPIDR_EL0`).
+    pub fn set_tpidr(&mut self, tpidr: usize) {
+        self.user_context.set_tls(tpidr);
+    }
+
+    /// Activates thread-local storage pointer on the current CPU.
+    pub fn activate_tls_pointer(&self) {
+        // No-op, since `TPIDR_EL0` is loaded when returning to the user space.
+    }
+
+    /// Returns the saved program status, i.e., the `PSTATE` of the user space.
+    pub fn pstate(&self) -> usize {
+        self.user_context.spsr
+    }
+
+    /// Sets the saved program status.
+    ///
+    /// Only the condition flags are kept when returning to the user space.
+    pub fn set_pstate(&mut self, pstate: usize) {
+        self.user_context.spsr = pstate;
+    }
+}
+
+impl UserContextApiInternal for UserContext {
+    fn execute<F>(&mut self, mut has_kernel_event: F) -> ReturnReason
+    where
+        F: FnMut() -> bool,
+    {
+        // Return to EL0 with the interrupts unmasked.
+        self.user_context.spsr &= SPSR_CONDITION_FLAGS;
+
+        let ret = loop {
+            scheduler::might_preempt();
+            match self.user_context.run() {
+                TrapKind::Irq => {
+                    handle_irq(&self.as_trap_frame());
+                    crate::arch::irq::enable_local();
+                }
+                TrapKind::Sync => {
+                    let (esr, far) = read_esr_and_far();
+                    let code = CpuException::from_esr(esr);
+                    if code == CpuException::Svc64 {
+                        // `ELR_EL1` already points to the instruction after the `svc`.
+                        break ReturnReason::UserSyscall;
+                    }
+                    log::trace!("Exception, esr: {esr:#x?}, far: {far:#x?}");
+                    self.cpu_exception_info = CpuExceptionInfo {
+                        code,
+                        page_fault_addr: far,
+                        error_code: esr,
+                    };
+                    break ReturnReason::UserException;
+                }
+                kind => {
+                    let (esr, _) = read_esr_and_far();
+                    panic!(
+                        "Cannot handle user cpu exception: {kind:?}. esr: {esr:#x}, trapframe: {:#x?}.",
+                        self.as_trap_frame()
+                    );
+                }
+            }
+
+            if has_kernel_event() {
+                break ReturnReason::KernelEvent;
+            }
+        };
+
+        crate::arch::irq::enable_local();
+        ret
+    }
+
+    fn as_trap_frame(&self) -> TrapFrame {
+        TrapFrame {
+            general: self.user_context.general,
+            elr: self.user_context.elr,
+            spsr: self.user_context.spsr,
+        }
+    }
+}
+
+impl UserContextApi for UserContext {
+    fn trap_number(&self) -> usize {
+        self.cpu_exception_info.code as usize
+    }
+
+    fn trap_error_code(&self) -> usize {
+        self.cpu_exception_info.error_code
+    }
+
+    fn instruction_pointer(&self) -> usize {
+        self.user_context.elr
+    }
+
+    fn set_instruction_pointer(&mut self, ip: usize) {
+        self.user_context.set_ip(ip);
+    }
+
+    fn stack_pointer(&self) -> usize {
+        self.user_context.get_sp()
+    }
+
+    fn set_stack_pointer(&mut self, sp: usize) {
+        self.user_context.set_sp(sp);
+    }
+}
+
+macro_rules! cpu_context_impl_getter_setter {
+    ( $( [ $field: ident, $setter_name: ident] ),*) => {
+        impl UserContext {
+            $(
+                #[doc = concat!("Gets the value of ", stringify!($field))]
+                #[inline(always)]
+                pub fn $field(&self) -> usize {
+                    self.user_context.general.$field
+                }
+
+                #[doc = concat!("Sets the value of ", stringify!($field))]
+                #[inline(always)]
+                pub fn $setter_name(&mut self, $field: usize) {
+                    self.user_context.general.$field = $field;
+                }
+            )*
+        }
+    };
+}
+
+cpu_context_impl_getter_setter!(
+    [x0, set_x0],
+    [x1, set_x1],
+    [x2, set_x2],
+    [x3, set_x3],
+    [x4, set_x4],
+    [x5, set_x5],
+    [x6, set_x6],
+    [x7, set_x7],
+    [x8, set_x8],
+    [x9, set_x9],
+    [x10, set_x10],
+    [x11, set_x11],
+    [x12, set_x12],
+    [x13, set_x13],
+    [x14, set_x14],
+    [x15, set_x15],
+    [x16, set_x16],
+    [x17, set_x17],
+    [x18, set_x18],
+    [x19, set_x19],
+    [x20, set_x20],
+    [x21, set_x21],
+    [x22, set_x22],
+    [x23, set_x23],
+    [x24, set_x24],
+    [x25, set_x25],
+    [x26, set_x26],
+    [x27, set_x27],
+    [x28, set_x28],
+    [x29, set_x29],
+    [x30, set_x30],
+    [sp, set_sp]
+);
+
+/// CPU exception.
+///
+/// The exceptions are identified by the exception classes (EC) in `ESR_EL1`. Only the
+/// classes that can be taken from the AArch64 user space are listed.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[repr(u8)]
+pub enum CpuException {
+    /// Unknown reason, e.g., an undefined instruction.
+    #[default]
+    Unknown = 0x00,
+    /// A trapped `WFI` or `WFE` instruction.
+    WfiWfe = 0x01,
+    /// An illegal execution state.
+    IllegalExecutionState = 0x0e,
+    /// A system call (`SVC` in AArch64).
+    Svc64 = 0x15,
+    /// A trapped access to a system register.
+    SysRegTrap = 0x18,
+    /// An instruction abort from a lower exception level.
+    InstructionAbortLowerEl = 0x20,
+    /// A misaligned PC.
+    PcAlignmentFault = 0x22,
+    /// A data abort from a lower exception level.
+    DataAbortLowerEl = 0x24,
+    /// A misaligned SP.
+    SpAlignmentFault = 0x26,
+    /// A trapped floating-point exception.
+    FpException = 0x2c,
+    /// A system error.
+    SError = 0x2f,
+    /// A breakpoint from a lower exception level.
+    BreakpointLowerEl = 0x30,
+    /// A software step from a lower exception level.
+    SoftwareStepLowerEl = 0x32,
+    /// A watchpoint from a lower exception level.
+    WatchpointLowerEl = 0x34,
+    /// A `BRK` instruction.
+    Brk64 = 0x3c,
+}
+
+impl CpuException {
+    /// Returns the exception of the syndrome (`ESR_EL1`).
+    pub fn from_esr(esr: usize) -> Self {
+        match (esr >> ESR_EC_SHIFT) & 0x3f {
+            0x01 => Self::WfiWfe,
+            0x0e => Self::IllegalExecutionState,
+            0x15 => Self::Svc64,
+            0x18 => Self::SysRegTrap,
+            0x20 => Self::InstructionAbortLowerEl,
+            0x22 => Self::PcAlignmentFault,
+            0x24 => Self::DataAbortLowerEl,
+            0x26 => Self::SpAlignmentFault,
+            0x2c => Self::FpException,
+            0x2f => Self::SError,
+            0x30 => Self::BreakpointLowerEl,
+            0x32 => Self::SoftwareStepLowerEl,
+            0x34 => Self::WatchpointLowerEl,
+            0x3c => Self::Brk64,
+            _ => Self::Unknown,
+        }
+    }
+}
+
+/// The FPU state of user task.
+///
+/// It holds the FP/SIMD registers, which the kernel itself does not use.
+#[derive(Debug)]
+pub struct FpuState {
+    state_area: Box<FpSimdArea>,
+    is_valid: AtomicBool,
+}
+
+/// The FP/SIMD registers, i.e., `Q0` to `Q31`, `FPCR` and `FPSR`.
+#[repr(C, align(16))]
+#[derive(Clone, Copy, Debug, Default)]
+struct FpSimdArea {
+    q: [u128; 32],
+    fpcr: u64,
+    fpsr: u64,
+}
+
+impl FpuState {
+    /// Initializes a new instance.
+    pub fn init() -> Self {
+        Self {
+            state_area: Box::default(),
+            is_valid: AtomicBool::new(true),
+        }
+    }
+
+    /// Returns whether the instance can contains valid state.
+    pub fn is_valid(&self) -> bool {
+        self.is_valid.load(Relaxed)
+    }
+
+    /// Save CPU's current FPU state into this instance.
+    pub fn save(&self) {
+        let mem_addr = &*self.state_area as *const FpSimdArea as *mut FpSimdArea;
+
+        // SAFETY: The area is large enough to hold the registers and is only accessed
+        // by the current task.
+        unsafe {
+            asm!(
+                ".arch_extension fp",
+                ".arch_extension simd",
+                "stp q0, q1, [{addr}, #0 * 32]",
+                "stp q2, q3, [{addr}, #1 * 32]",
+                "stp q4, q5, [{addr}, #2 * 32]",
+                "stp q6, q7, [{addr}, #3 * 32]",
+                "stp q8, q9, [{addr}, #4 * 32]",
+                "stp q10, q11, [{addr}, #5 * 32]",
+                "stp q12, q13, [{addr}, #6 * 32]",
+                "stp q14, q15, [{addr}, #7 * 32]",
+                "stp q16, q17, [{addr}, #8 * 32]",
+                "stp q18, q19, [{addr}, #9 * 32]",
+                "stp q20, q21, [{addr}, #10 * 32]",
+                "stp q22, q23, [{addr}, #11 * 32]",
+                "stp q24, q25, [{addr}, #12 * 32]",
+                "stp q26, q27, [{addr}, #13 * 32]",
+                "stp q28, q29, [{addr}, #14 * 32]",
+                "stp q30, q31, [{addr}, #15 * 32]",
+                "mrs {tmp}, fpcr",
+                "str {tmp}, [{addr}, #16 * 32]",
+                "mrs {tmp}, fpsr",
+                "str {tmp}, [{addr}, #16 * 32 + 8]",
+                addr = in(reg) mem_addr,
+                tmp = out(reg) _,
+                options(nostack),
+            );
+        }
+
+        self.is_valid.store(true, Relaxed);
+
+        debug!("Save FPU state");
+    }
+
+    /// Restores CPU's FPU state from this instance.
+    pub fn restore(&self) {
+        if !self.is_valid() {
+            return;
+        }
+
+        let mem_addr = &*self.state_area as *const FpSimdArea;
+
+        // SAFETY: The area holds the registers saved by `save` or the initial ones.
+        unsafe {
+            asm!(
+                ".arch_extension fp",
+                ".arch_extension simd",
+                "ldp q0, q1, [{addr}, #0 * 32]",
+                "ldp q2, q3, [{addr}, #1 * 32]",
+                "ldp q4, q5, [{addr}, #2 * 32]",
+                "ldp q6, q7, [{addr}, #3 * 32]",
+                "ldp q8, q9, [{addr}, #4 * 32]",
+                "ldp q10, q11, [{addr}, #5 * 32]",
+                "ldp q12, q13, [{addr}, #6 * 32]",
+                "ldp q14, q15, [{addr}, #7 * 32]",
+                "ldp q16, q17, [{addr}, #8 * 32]",
+                "ldp q18, q19, [{addr}, #9 * 32]",
+                "ldp q20, q21, [{addr}, #10 * 32]",
+                "ldp q22, q23, [{addr}, #11 * 32]",
+                "ldp q24, q25, [{addr}, #12 * 32]",
+                "ldp q26, q27, [{addr}, #13 * 32]",
+                "ldp q28, q29, [{addr}, #14 * 32]",
+                "ldp q30, q31, [{addr}, #15 * 32]",
+                "ldr {tmp}, [{addr}, #16 * 32]",
+                "msr fpcr, {tmp}",
+                "ldr {tmp}, [{addr}, #16 * 32 + 8]",
+                "msr fpsr, {tmp}",
+                addr = in(reg) mem_addr,
+                tmp = out(reg) _,
+                options(nostack, readonly),
+            );
+        }
+
+        self.is_valid.store(false, Relaxed);
+
+        debug!("Restore FPU state");
+    }
+
+    /// Clears the state of the instance.
+    ///
+    /// This method does not reset the underlying buffer that contains the
+    /// FPU state; it only marks the buffer __invalid__.
+    pub fn clear(&self) {
+        self.is_valid.store(false, Relaxed);
+    }
+}
+
+impl Clone for FpuState {
+    fn clone(&self) -> Self {
+        Self {
+            state_area: self.state_area.clone(),
+            is_valid: AtomicBool::new(self.is_valid()),
+        }
+    }
+}
+
+impl Default for FpuState {
+    fn default() -> Self {
+        Self::init()
+    }
+}
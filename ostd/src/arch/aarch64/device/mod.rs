@@ -0,0 +1,6 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! Device-related APIs.
+//! This module mainly contains the APIs that should exposed to the device driver like PCI, RTC
+
+pub mod io_port;
@@ -0,0 +1,31 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! The frequencies of AArch64 CPUs.
+//!
+//! The frequencies of the CPUs are not reported by the PSCI, and the device
+//! tree properties and the SCMI performance domains that describe them are not
+//! parsed yet, so they are unknown.
+
+use crate::{cpu::freq::FreqInfo, trap::DisabledLocalIrqGuard};
+
+pub(crate) fn probe() -> FreqInfo {
+    FreqInfo {
+        min_khz: 0,
+        base_khz: 0,
+        max_khz: 0,
+    }
+}
+
+pub(crate) fn measure_khz(_guard: &DisabledLocalIrqGuard, _info: &FreqInfo) -> Option<u64> {
+    None
+}
+
+pub(crate) fn can_scale() -> bool {
+    false
+}
+
+pub(crate) fn driver_name() -> Option<&'static str> {
+    None
+}
+
+pub(crate) fn set_target_khz(_guard: &DisabledLocalIrqGuard, _target_khz: u64) {}
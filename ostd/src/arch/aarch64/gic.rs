@@ -0,0 +1,454 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! The Generic Interrupt Controller version 3 (GICv3).
+//!
+//! The distributor routes the shared peripheral interrupts (SPIs) of the devices
+//! to the CPUs. The redistributor of each CPU manages the private peripheral
+//! interrupts (PPIs) of the CPU, e.g., the timer interrupts, and the
+//! software-generated interrupts (SGIs), i.e., the IPIs. Each CPU acknowledges an
+//! interrupt with the system registers of its CPU interface before the interrupt
+//! is handled, and it must complete the interrupt afterwards so that the
+//! interrupt can be signaled again.
+//!
+//! All the interrupts are in the non-secure group 1 and have the same priority.
+//!
+//! Ref: <https://developer.arm.com/documentation/ihi0069/latest>
+
+use alloc::vec::Vec;
+use core::{
+    arch::asm,
+    sync::atomic::{AtomicU32, Ordering},
+};
+
+use log::info;
+use spin::Once;
+
+use super::boot::{smp::mpidr_of, DEVICE_TREE};
+use crate::{
+    cpu::CpuId,
+    cpu_local_cell,
+    mm::{paddr_to_vaddr, Vaddr},
+    sync::SpinLock,
+    trap::IrqLine,
+    Error, Result,
+};
+
+const GICD_CTLR: usize = 0x0000;
+const GICD_TYPER: usize = 0x0004;
+const GICD_IGROUPR: usize = 0x0080;
+const GICD_ISENABLER: usize = 0x0100;
+const GICD_ICENABLER: usize = 0x0180;
+const GICD_IPRIORITYR: usize = 0x0400;
+const GICD_IROUTER: usize = 0x6000;
+
+const GICD_CTLR_ENABLE_GRP1: u32 = 1 << 1;
+const GICD_CTLR_ARE: u32 = 1 << 4;
+const GICD_CTLR_RWP: u32 = 1 << 31;
+
+const GICR_TYPER: usize = 0x0008;
+const GICR_WAKER: usize = 0x0014;
+/// The offset of the SGI and PPI registers (the `SGI_base` frame) of a redistributor.
+const GICR_SGI_BASE: usize = 0x1_0000;
+const GICR_IGROUPR0: usize = GICR_SGI_BASE + 0x0080;
+const GICR_ISENABLER0: usize = GICR_SGI_BASE + 0x0100;
+const GICR_ICENABLER0: usize = GICR_SGI_BASE + 0x0180;
+const GICR_IPRIORITYR: usize = GICR_SGI_BASE + 0x0400;
+
+const GICR_TYPER_VLPIS: u64 = 1 << 1;
+const GICR_TYPER_LAST: u64 = 1 << 4;
+const GICR_WAKER_PROCESSOR_SLEEP: u32 = 1 << 1;
+const GICR_WAKER_CHILDREN_ASLEEP: u32 = 1 << 2;
+
+/// The first INTID of the PPIs.
+pub(super) const PPI_BASE: u32 = 16;
+/// The first INTID of the SPIs.
+pub(super) const SPI_BASE: u32 = 32;
+/// The first special INTID, which is returned if no interrupt is pending.
+const SPECIAL_INTID_BASE: u32 = 1020;
+
+/// The SGI that is sent for the IPIs.
+pub(super) const IPI_SGI: u32 = 0;
+
+/// The priority of all the interrupts, which is higher than the priority mask.
+const DEFAULT_PRIORITY: u32 = 0xa0;
+
+/// The value in [`Gic::irq_nums`] for the INTIDs without IRQ lines.
+const NO_IRQ: u32 = u32::MAX;
+/// The value of [`ACKED_INTID`] if no interrupt is acknowledged.
+const NO_INTID: u32 = u32::MAX;
+
+pub(crate) static GIC: Once<Gic> = Once::new();
+
+cpu_local_cell! {
+    /// The virtual address of the redistributor of the current CPU.
+    static REDIST_BASE: usize = 0;
+    /// The INTID that is acknowledged but not completed on the current CPU, or
+    /// [`NO_INTID`] if there is none.
+    static ACKED_INTID: u32 = NO_INTID;
+}
+
+/// The Generic Interrupt Controller.
+pub(crate) struct Gic {
+    /// The virtual address of the distributor registers in the linear mapping.
+    ///
+    /// The registers are accessed before the kernel page table is activated, so they
+    /// cannot be mapped as an `IoMem`.
+    dist_base: Vaddr,
+    /// The virtual address of the redistributor of each CPU in the linear mapping,
+    /// indexed by the CPU IDs.
+    redist_bases: Vec<Vaddr>,
+    /// The number of the INTIDs of the SGIs, the PPIs and the SPIs.
+    num_intids: u32,
+    /// The IRQ number of each INTID.
+    irq_nums: Vec<AtomicU32>,
+    /// The IRQ lines of the enabled SPIs.
+    irqs: SpinLock<Vec<(u32, IrqLine)>>,
+}
+
+impl Gic {
+    /// Enables an SPI, delivering its interrupts to the IRQ line.
+    ///
+    /// TODO: Deliver the interrupts to the CPUs other than the BSP.
+    pub fn enable(&self, intid: u32, irq: IrqLine) -> Result<()> {
+        if !(SPI_BASE..self.num_intids).contains(&intid) {
+            return Err(Error::InvalidArgs);
+        }
+
+        let mut irqs = self.irqs.lock();
+        if irqs
+            .iter()
+            .any(|(enabled_intid, _)| *enabled_intid == intid)
+        {
+            return Err(Error::AccessDenied);
+        }
+
+        self.irq_nums[intid as usize].store(irq.num() as u32, Ordering::Relaxed);
+        // SAFETY: The register routes the SPI, which is valid, to the BSP.
+        unsafe {
+            write64(
+                self.dist_base + GICD_IROUTER + 8 * intid as usize,
+                mpidr_of(CpuId::bsp()),
+            )
+        };
+        self.write_dist(
+            GICD_ISENABLER + 4 * (intid as usize / 32),
+            1 << (intid % 32),
+        );
+        irqs.push((intid, irq));
+        Ok(())
+    }
+
+    /// Disables an SPI.
+    pub fn disable(&self, intid: u32) -> Result<()> {
+        if !(SPI_BASE..self.num_intids).contains(&intid) {
+            return Err(Error::InvalidArgs);
+        }
+
+        let mut irqs = self.irqs.lock();
+        self.write_dist(
+            GICD_ICENABLER + 4 * (intid as usize / 32),
+            1 << (intid % 32),
+        );
+        self.wait_for_dist_writes();
+        self.irq_nums[intid as usize].store(NO_IRQ, Ordering::Relaxed);
+        irqs.retain(|(enabled_intid, _)| *enabled_intid != intid);
+        Ok(())
+    }
+
+    /// Reads a register of the distributor.
+    fn read_dist(&self, offset: usize) -> u32 {
+        // SAFETY: The offset is a register of the distributor, which is mapped in the linear
+        // mapping.
+        unsafe { read32(self.dist_base + offset) }
+    }
+
+    /// Writes a register of the distributor.
+    fn write_dist(&self, offset: usize, val: u32) {
+        // SAFETY: The offset is a register of the distributor, which is mapped in the linear
+        // mapping.
+        unsafe { write32(self.dist_base + offset, val) }
+    }
+
+    /// Waits until the writes to the distributor control registers take effect.
+    fn wait_for_dist_writes(&self) {
+        while self.read_dist(GICD_CTLR) & GICD_CTLR_RWP != 0 {
+            core::hint::spin_loop();
+        }
+    }
+
+    fn init_distributor(&self) {
+        self.write_dist(GICD_CTLR, 0);
+        self.wait_for_dist_writes();
+
+        // Put all the SPIs in group 1 with the default priority, and disable them.
+        for intid in (SPI_BASE..self.num_intids).step_by(32) {
+            let offset = 4 * (intid as usize / 32);
+            self.write_dist(GICD_IGROUPR + offset, u32::MAX);
+            self.write_dist(GICD_ICENABLER + offset, u32::MAX);
+        }
+        for intid in (SPI_BASE..self.num_intids).step_by(4) {
+            self.write_dist(GICD_IPRIORITYR + intid as usize, priority_word());
+        }
+        self.wait_for_dist_writes();
+
+        self.write_dist(GICD_CTLR, GICD_CTLR_ARE | GICD_CTLR_ENABLE_GRP1);
+        self.wait_for_dist_writes();
+    }
+}
+
+/// Initializes the GIC and the redistributor and the CPU interface of the BSP.
+pub(super) fn init() {
+    let device_tree = DEVICE_TREE.get().unwrap();
+    let Some(node) = device_tree.find_compatible(&["arm,gic-v3"]) else {
+        log::warn!("[GIC]: No GICv3 found. Interrupts are disabled.");
+        return;
+    };
+
+    // The `reg` property lists the distributor and then the redistributor regions.
+    let mut regs = node.reg().unwrap();
+    let dist_paddr = regs.next().unwrap().starting_address as usize;
+    let num_redist_regions = node
+        .property("#redistributor-regions")
+        .and_then(|num| num.as_usize())
+        .unwrap_or(1);
+    let redist_regions: Vec<(usize, usize)> = regs
+        .take(num_redist_regions)
+        .map(|region| (region.starting_address as usize, region.size.unwrap()))
+        .collect();
+
+    let dist_base = paddr_to_vaddr(dist_paddr);
+    // SAFETY: The register is the type register of the distributor.
+    let typer = unsafe { read32(dist_base + GICD_TYPER) };
+    let num_intids = (32 * ((typer & 0x1f) + 1)).min(SPECIAL_INTID_BASE);
+
+    let redist_bases = crate::cpu::all_cpus()
+        .map(|cpu_id| find_redistributor(&redist_regions, mpidr_of(cpu_id)))
+        .collect();
+
+    let irq_nums = (0..num_intids).map(|_| AtomicU32::new(NO_IRQ)).collect();
+
+    info!(
+        "[GIC]: Found GICv3 at {:#x} with {} interrupt IDs",
+        dist_paddr, num_intids
+    );
+    let gic = GIC.call_once(|| Gic {
+        dist_base,
+        redist_bases,
+        num_intids,
+        irq_nums,
+        irqs: SpinLock::new(Vec::new()),
+    });
+    gic.init_distributor();
+
+    init_current_cpu(CpuId::bsp());
+}
+
+/// Initializes the redistributor and the CPU interface of the current AP.
+pub(super) fn init_on_ap(cpu_id: CpuId) {
+    init_current_cpu(cpu_id);
+}
+
+fn init_current_cpu(cpu_id: CpuId) {
+    let Some(gic) = GIC.get() else {
+        return;
+    };
+
+    let redist_base = gic.redist_bases[cpu_id.as_usize()];
+    REDIST_BASE.store(redist_base);
+
+    // SAFETY: The registers are the registers of the redistributor of the current CPU, which
+    // is mapped in the linear mapping. The SGIs and the PPIs are disabled except the IPIs,
+    // which are handled by the trap handler.
+    unsafe {
+        // Wake up the redistributor.
+        let waker = read32(redist_base + GICR_WAKER);
+        write32(
+            redist_base + GICR_WAKER,
+            waker & !GICR_WAKER_PROCESSOR_SLEEP,
+        );
+        while read32(redist_base + GICR_WAKER) & GICR_WAKER_CHILDREN_ASLEEP != 0 {
+            core::hint::spin_loop();
+        }
+
+        write32(redist_base + GICR_IGROUPR0, u32::MAX);
+        write32(redist_base + GICR_ICENABLER0, u32::MAX);
+        for intid in (0..SPI_BASE).step_by(4) {
+            write32(
+                redist_base + GICR_IPRIORITYR + intid as usize,
+                priority_word(),
+            );
+        }
+        write32(redist_base + GICR_ISENABLER0, 1 << IPI_SGI);
+    }
+
+    // SAFETY: Enabling the system register interface and the group 1 interrupts of the CPU
+    // interface does not affect memory safety. All the priorities are below the mask.
+    unsafe {
+        asm!(
+            "mrs {tmp}, icc_sre_el1",
+            "orr {tmp}, {tmp}, #1",
+            "msr icc_sre_el1, {tmp}",
+            "isb",
+            "msr icc_pmr_el1, {pmr}",
+            "msr icc_bpr1_el1, xzr",
+            "msr icc_igrpen1_el1, {enable}",
+            "isb",
+            tmp = out(reg) _,
+            pmr = in(reg) 0xffusize,
+            enable = in(reg) 1usize,
+            options(nostack),
+        );
+    }
+}
+
+/// Enables a PPI on the current CPU, delivering its interrupts to the IRQ number.
+pub(super) fn enable_local_ppi(intid: u32, irq_num: u8) {
+    let Some(gic) = GIC.get() else {
+        return;
+    };
+    debug_assert!((PPI_BASE..SPI_BASE).contains(&intid));
+
+    gic.irq_nums[intid as usize].store(irq_num as u32, Ordering::Relaxed);
+    // SAFETY: The register enables the PPI of the current CPU, which is handled by the trap
+    // handler.
+    unsafe { write32(REDIST_BASE.load() + GICR_ISENABLER0, 1 << intid) };
+}
+
+/// Acknowledges a pending interrupt on the current CPU, returning its INTID.
+///
+/// The interrupt is completed by [`complete`] after it is handled.
+pub(super) fn ack() -> Option<u32> {
+    GIC.get()?;
+
+    let intid: u64;
+    // SAFETY: Acknowledging an interrupt does not affect memory safety.
+    unsafe { asm!("mrs {}, icc_iar1_el1", out(reg) intid, options(nostack)) };
+    let intid = intid as u32 & 0xff_ffff;
+    if (SPECIAL_INTID_BASE..1024).contains(&intid) {
+        return None;
+    }
+
+    ACKED_INTID.store(intid);
+    Some(intid)
+}
+
+/// Returns the IRQ number that an INTID is delivered to, if any.
+pub(super) fn irq_num_of(intid: u32) -> Option<u8> {
+    let irq_num = GIC
+        .get()?
+        .irq_nums
+        .get(intid as usize)?
+        .load(Ordering::Relaxed);
+    (irq_num != NO_IRQ).then_some(irq_num as u8)
+}
+
+/// Completes the interrupt acknowledged on the current CPU, if any.
+pub(super) fn complete() {
+    let intid = ACKED_INTID.load();
+    if intid == NO_INTID {
+        return;
+    }
+    ACKED_INTID.store(NO_INTID);
+
+    // SAFETY: Completing the acknowledged interrupt does not affect memory safety.
+    unsafe { asm!("msr icc_eoir1_el1, {}", in(reg) intid as u64, options(nostack)) };
+}
+
+/// Sends an SGI to the CPU with the affinity value `mpidr`.
+pub(super) fn send_sgi(mpidr: u64, sgi: u32) {
+    let aff0 = mpidr & 0xff;
+    let aff1 = (mpidr >> 8) & 0xff;
+    let aff2 = (mpidr >> 16) & 0xff;
+    let aff3 = (mpidr >> 32) & 0xff;
+    let val = (aff3 << 48)
+        | ((aff0 >> 4) << 44)
+        | (aff2 << 32)
+        | ((sgi as u64) << 24)
+        | (aff1 << 16)
+        | (1 << (aff0 & 0xf));
+
+    // SAFETY: Sending an SGI does not affect memory safety. The memory writes before it are
+    // made visible to the target CPU first.
+    unsafe { asm!("dsb ishst", "msr icc_sgi1r_el1, {}", "isb", in(reg) val, options(nostack)) };
+}
+
+/// Parses the `index`-th interrupt in the `interrupts` property of a device tree
+/// node whose interrupt parent is the GIC, returning its INTID.
+///
+/// Each interrupt has three cells: the type (0 for SPIs and 1 for PPIs), the
+/// interrupt number within the type, and the flags.
+pub(crate) fn parse_interrupt(interrupts: &[u8], index: usize) -> Option<u32> {
+    let cells = interrupts.chunks_exact(12).nth(index)?;
+    let typ = u32::from_be_bytes(cells[0..4].try_into().unwrap());
+    let num = u32::from_be_bytes(cells[4..8].try_into().unwrap());
+    match typ {
+        0 => Some(SPI_BASE + num),
+        1 => Some(PPI_BASE + num),
+        _ => None,
+    }
+}
+
+/// Finds the redistributor of the CPU with the affinity value `mpidr`.
+fn find_redistributor(regions: &[(usize, usize)], mpidr: u64) -> Vaddr {
+    // The affinity value in `GICR_TYPER` is in the format of Aff3.Aff2.Aff1.Aff0.
+    let affinity = ((mpidr >> 8) & 0xff00_0000) | (mpidr & 0xff_ffff);
+
+    for &(start, size) in regions {
+        let mut offset = 0;
+        while offset < size {
+            let base = paddr_to_vaddr(start + offset);
+            // SAFETY: The register is the type register of a redistributor.
+            let typer = unsafe { read64(base + GICR_TYPER) };
+            if typer >> 32 == affinity {
+                return base;
+            }
+            if typer & GICR_TYPER_LAST != 0 {
+                break;
+            }
+            // The redistributors with virtual LPIs have two more 64 KiB frames.
+            offset += if typer & GICR_TYPER_VLPIS != 0 {
+                0x4_0000
+            } else {
+                0x2_0000
+            };
+        }
+    }
+
+    panic!("[GIC]: No redistributor found for MPIDR {:#x}", mpidr);
+}
+
+/// Returns the value of an `IPRIORITYR` register that sets four interrupts to the
+/// default priority.
+const fn priority_word() -> u32 {
+    DEFAULT_PRIORITY * 0x0101_0101
+}
+
+/// # Safety
+///
+/// The address must be a readable register of the GIC.
+unsafe fn read32(addr: Vaddr) -> u32 {
+    core::ptr::read_volatile(addr as *const u32)
+}
+
+/// # Safety
+///
+/// The address must be a writable register of the GIC, and the write must not
+/// affect memory safety.
+unsafe fn write32(addr: Vaddr, val: u32) {
+    core::ptr::write_volatile(addr as *mut u32, val)
+}
+
+/// # Safety
+///
+/// The address must be a readable 64-bit register of the GIC.
+unsafe fn read64(addr: Vaddr) -> u64 {
+    core::ptr::read_volatile(addr as *const u64)
+}
+
+/// # Safety
+///
+/// The address must be a writable 64-bit register of the GIC, and the write must
+/// not affect memory safety.
+unsafe fn write64(addr: Vaddr, val: u64) {
+    core::ptr::write_volatile(addr as *mut u64, val)
+}
@@ -0,0 +1,26 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! The idle states of AArch64 CPUs.
+//!
+//! Only `WFI` is supported. The deeper states of the PSCI `CPU_SUSPEND` call
+//! need the idle states described by the device tree, which are not parsed yet.
+
+use crate::{cpu::idle::IdleState, trap::DisabledLocalIrqGuard};
+
+const WFI_STATE: IdleState = IdleState::new("WFI", "ARM WFI", 1_000, 1_000, 0);
+
+pub(crate) fn init() {}
+
+pub(crate) fn states() -> &'static [IdleState] {
+    &[WFI_STATE]
+}
+
+pub(crate) fn is_virtualized() -> bool {
+    // There is no standard way to detect the hypervisors.
+    false
+}
+
+/// Enters the idle state until the next interrupt and then enables local IRQs.
+pub(crate) fn enter_state(_guard: &DisabledLocalIrqGuard, _state: &IdleState) {
+    super::irq::enable_local_and_halt();
+}
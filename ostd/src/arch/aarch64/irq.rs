@@ -0,0 +1,224 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! Interrupts.
+
+use alloc::{boxed::Box, fmt::Debug, sync::Arc, vec::Vec};
+use core::sync::atomic::{AtomicU64, Ordering};
+
+use id_alloc::IdAlloc;
+use spin::Once;
+
+use super::{boot::smp::mpidr_of, gic};
+use crate::{
+    cpu::{CpuId, PinCurrentCpu},
+    cpu_local,
+    sync::{Mutex, PreemptDisabled, SpinLock, SpinLockGuard},
+    trap::{call_irq_callback_functions, TrapFrame},
+    Error, Result,
+};
+
+/// The global allocator for software defined IRQ lines.
+pub(crate) static IRQ_ALLOCATOR: Once<SpinLock<IdAlloc>> = Once::new();
+
+pub(crate) static IRQ_LIST: Once<Vec<IrqLine>> = Once::new();
+
+pub(crate) fn init() {
+    let mut list: Vec<IrqLine> = Vec::new();
+    for i in 0..256 {
+        list.push(IrqLine {
+            irq_num: i as u8,
+            callback_list: SpinLock::new(Vec::new()),
+        });
+    }
+    IRQ_LIST.call_once(|| list);
+    CALLBACK_ID_ALLOCATOR.call_once(|| Mutex::new(IdAlloc::with_capacity(256)));
+    IRQ_ALLOCATOR.call_once(|| SpinLock::new(IdAlloc::with_capacity(256)));
+}
+
+pub(crate) fn enable_local() {
+    // SAFETY: The IRQs are handled by the trap handler.
+    unsafe { core::arch::asm!("msr daifclr, #2", options(nostack)) };
+}
+
+pub(crate) fn disable_local() {
+    // SAFETY: Masking the IRQs does not affect memory safety.
+    unsafe { core::arch::asm!("msr daifset, #2", options(nostack)) };
+}
+
+/// Halts the CPU until the next interrupt and then enables local IRQs.
+///
+/// A pending interrupt wakes up the CPU even if local IRQs are disabled, so
+/// the interrupt is handled right after the IRQs are enabled.
+pub(crate) fn enable_local_and_halt() {
+    // SAFETY: Waiting for interrupts does not affect memory safety.
+    unsafe { core::arch::asm!("wfi") };
+    enable_local();
+}
+
+pub(crate) fn is_local_enabled() -> bool {
+    const DAIF_I: u64 = 1 << 7;
+
+    let daif: u64;
+    // SAFETY: Reading `DAIF` has no side effects.
+    unsafe { core::arch::asm!("mrs {}, daif", out(reg) daif, options(nomem, nostack)) };
+    daif & DAIF_I == 0
+}
+
+static CALLBACK_ID_ALLOCATOR: Once<Mutex<IdAlloc>> = Once::new();
+
+pub struct CallbackElement {
+    function: Box<dyn Fn(&TrapFrame) + Send + Sync + 'static>,
+    id: usize,
+}
+
+impl CallbackElement {
+    pub fn call(&self, element: &TrapFrame) {
+        self.function.call((element,));
+    }
+}
+
+impl Debug for CallbackElement {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("CallbackElement")
+            .field("id", &self.id)
+            .finish()
+    }
+}
+
+/// An interrupt request (IRQ) line.
+#[derive(Debug)]
+pub(crate) struct IrqLine {
+    pub(crate) irq_num: u8,
+    pub(crate) callback_list: SpinLock<Vec<CallbackElement>>,
+}
+
+impl IrqLine {
+    /// Acquire an interrupt request line.
+    ///
+    /// # Safety
+    ///
+    /// This function is marked unsafe as manipulating interrupt lines is
+    /// considered a dangerous operation.
+    #[allow(clippy::redundant_allocation)]
+    pub unsafe fn acquire(irq_num: u8) -> Arc<&'static Self> {
+        Arc::new(IRQ_LIST.get().unwrap().get(irq_num as usize).unwrap())
+    }
+
+    /// Get the IRQ number.
+    pub fn num(&self) -> u8 {
+        self.irq_num
+    }
+
+    /// Returns the CPU that the IRQ is delivered to.
+    pub fn affinity(&self) -> CpuId {
+        CpuId::bsp()
+    }
+
+    /// Delivers the IRQ to a CPU.
+    ///
+    /// TODO: Route the SPIs to other CPUs with the GIC distributor. For now, they
+    /// are always delivered to the BSP.
+    pub fn set_affinity(&self, cpu_id: CpuId) -> Result<()> {
+        if cpu_id != CpuId::bsp() {
+            return Err(Error::InvalidArgs);
+        }
+        Ok(())
+    }
+
+    /// Resets the affinity after the IRQ line is freed.
+    pub(crate) fn reset_affinity(&self) {}
+
+    pub fn callback_list(
+        &self,
+    ) -> SpinLockGuard<alloc::vec::Vec<CallbackElement>, PreemptDisabled> {
+        self.callback_list.lock()
+    }
+
+    /// Register a callback that will be invoked when the IRQ is active.
+    ///
+    /// A handle to the callback is returned. Dropping the handle
+    /// automatically unregisters the callback.
+    ///
+    /// For each IRQ line, multiple callbacks may be registered.
+    pub fn on_active<F>(&self, callback: F) -> IrqCallbackHandle
+    where
+        F: Fn(&TrapFrame) + Sync + Send + 'static,
+    {
+        let allocate_id = CALLBACK_ID_ALLOCATOR.get().unwrap().lock().alloc().unwrap();
+        self.callback_list.lock().push(CallbackElement {
+            function: Box::new(callback),
+            id: allocate_id,
+        });
+        IrqCallbackHandle {
+            irq_num: self.irq_num,
+            id: allocate_id,
+        }
+    }
+}
+
+/// The handle to a registered callback for a IRQ line.
+///
+/// When the handle is dropped, the callback will be unregistered automatically.
+#[must_use]
+#[derive(Debug)]
+pub struct IrqCallbackHandle {
+    irq_num: u8,
+    id: usize,
+}
+
+impl Drop for IrqCallbackHandle {
+    fn drop(&mut self) {
+        let mut a = IRQ_LIST
+            .get()
+            .unwrap()
+            .get(self.irq_num as usize)
+            .unwrap()
+            .callback_list
+            .lock();
+        a.retain(|item| item.id != self.id);
+        CALLBACK_ID_ALLOCATOR.get().unwrap().lock().free(self.id);
+    }
+}
+
+cpu_local! {
+    /// The IRQ numbers of the pending IPIs of each CPU, as a bitmap.
+    static PENDING_IPIS: [AtomicU64; 4] = [
+        AtomicU64::new(0),
+        AtomicU64::new(0),
+        AtomicU64::new(0),
+        AtomicU64::new(0),
+    ];
+}
+
+/// Sends a general inter-processor interrupt (IPI) to the specified CPU.
+///
+/// All the IPIs are sent as the same SGI, so the IRQ number is recorded in the
+/// pending IPIs of the target CPU before the SGI is sent.
+///
+/// # Safety
+///
+/// The caller must ensure that the CPU ID and the interrupt number corresponds
+/// to a safe function to call.
+pub(crate) unsafe fn send_ipi(cpu_id: CpuId, irq_num: u8) {
+    let pending_ipis = PENDING_IPIS.get_on_cpu(cpu_id);
+    pending_ipis[irq_num as usize / 64].fetch_or(1 << (irq_num % 64), Ordering::Release);
+
+    gic::send_sgi(mpidr_of(cpu_id), gic::IPI_SGI);
+}
+
+/// Handles the SGI acknowledged from the GIC, which indicates pending IPIs.
+pub(super) fn handle_ipi(trap_frame: &TrapFrame) {
+    // Complete the SGI before taking the pending IPIs, so that no IPI is missed.
+    gic::complete();
+
+    let irq_guard = crate::trap::disable_local();
+    let pending_ipis = PENDING_IPIS.get_on_cpu(irq_guard.current_cpu());
+    for (i, pending) in pending_ipis.iter().enumerate() {
+        let mut bits = pending.swap(0, Ordering::Acquire);
+        while bits != 0 {
+            let irq_num = i * 64 + bits.trailing_zeros() as usize;
+            bits &= bits - 1;
+            call_irq_callback_functions(trap_frame, irq_num);
+        }
+    }
+}
@@ -0,0 +1,338 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! The AArch64 MMU with the 4KiB translation granule and 48-bit virtual addresses.
+//!
+//! The kernel space and the user space share one root page table. It is installed in both
+//! `TTBR0_EL1` (for the lower half) and `TTBR1_EL1` (for the higher half), so the root page
+//! table is indexed in the same way as on the other architectures.
+//!
+//! The memory attributes in `MAIR_EL1` are set by the boot code:
+//! - Index 0: Normal memory, write-back cacheable;
+//! - Index 1: Device-nGnRE memory;
+//! - Index 2: Normal memory, non-cacheable.
+
+use alloc::fmt;
+use core::{arch::asm, ops::Range};
+
+use crate::{
+    cpu::CpuSet,
+    mm::{
+        page_prop::{CachePolicy, PageFlags, PageProperty, PrivilegedPageFlags as PrivFlags},
+        page_table::PageTableEntryTrait,
+        Paddr, PagingConstsTrait, PagingLevel, Vaddr, PAGE_SIZE,
+    },
+    Pod,
+};
+
+pub(crate) const NR_ENTRIES_PER_PAGE: usize = 512;
+
+#[derive(Clone, Debug, Default)]
+pub struct PagingConsts {}
+
+impl PagingConstsTrait for PagingConsts {
+    const BASE_PAGE_SIZE: usize = 4096;
+    const NR_LEVELS: PagingLevel = 4;
+    const ADDRESS_WIDTH: usize = 48;
+    const HIGHEST_TRANSLATION_LEVEL: PagingLevel = 3;
+    const PTE_SIZE: usize = core::mem::size_of::<PageTableEntry>();
+}
+
+bitflags::bitflags! {
+    #[derive(Pod)]
+    #[repr(C)]
+    /// Possible flags for a page table entry.
+    pub struct PageTableFlags: usize {
+        /// Specifies whether the mapped frame or page table is valid.
+        const VALID =           1 << 0;
+        /// Specifies that the entry points to a page table (at non-last levels) or a
+        /// page (at the last level), rather than a block.
+        const TABLE_OR_PAGE =   1 << 1;
+        /// The index of the memory attributes in `MAIR_EL1` (bit 0).
+        const ATTR_INDX_0 =     1 << 2;
+        /// The index of the memory attributes in `MAIR_EL1` (bit 1).
+        const ATTR_INDX_1 =     1 << 3;
+        /// Controls whether accesses from userspace (i.e. EL0) are permitted (`AP[1]`).
+        const USER =            1 << 6;
+        /// Controls whether writes to the mapped frames are forbidden (`AP[2]`).
+        const READ_ONLY =       1 << 7;
+        /// The mapped frames are inner shareable.
+        const INNER_SHAREABLE = 0b11 << 8;
+        /// The access flag, without which an access fault is raised.
+        const ACCESS =          1 << 10;
+        /// Indicates that the mapping is only valid in the current address space.
+        const NOT_GLOBAL =      1 << 11;
+        /// Forbids the execution in the kernel mode (i.e. EL1).
+        const PRIV_EXEC_NEVER = 1 << 53;
+        /// Forbids the execution in the user mode (i.e. EL0).
+        const USER_EXEC_NEVER = 1 << 54;
+
+        // The bits below are ignored by the MMU.
+
+        /// Whether the memory area represented by this entry is accessed.
+        ///
+        /// The hardware management of the access flag is not enabled, so the access flag is
+        /// always set and the state is kept in software.
+        const ACCESSED =        1 << 55;
+        /// Whether the memory area represented by this entry is modified.
+        const DIRTY =           1 << 56;
+        const AVAIL1 =          1 << 57;
+        const AVAIL2 =          1 << 58;
+    }
+}
+
+/// The attributes index of write-back normal memory.
+const ATTR_INDX_NORMAL: usize = 0;
+/// The attributes index of device memory.
+const ATTR_INDX_DEVICE: usize = 1;
+/// The attributes index of non-cacheable normal memory.
+const ATTR_INDX_NORMAL_NC: usize = 2;
+const ATTR_INDX_SHIFT: usize = 2;
+const ATTR_INDX_MASK: usize = 0b111 << ATTR_INDX_SHIFT;
+
+pub(crate) fn tlb_flush_addr(vaddr: Vaddr) {
+    // SAFETY: Invalidating the TLB entries does not affect memory safety.
+    unsafe {
+        asm!(
+            "dsb ishst",
+            "tlbi vaae1, {}",
+            "dsb nsh",
+            "isb",
+            in(reg) (vaddr >> 12) & ((1 << 44) - 1),
+        );
+    }
+}
+
+pub(crate) fn tlb_flush_addr_range(range: &Range<Vaddr>) {
+    for vaddr in range.clone().step_by(PAGE_SIZE) {
+        tlb_flush_addr(vaddr);
+    }
+}
+
+pub(crate) fn tlb_flush_all_excluding_global() {
+    // Without ASIDs, the non-global entries cannot be invalidated alone.
+    tlb_flush_all_including_global();
+}
+
+pub(crate) fn tlb_flush_all_including_global() {
+    // SAFETY: Invalidating the TLB entries does not affect memory safety.
+    unsafe { asm!("dsb ishst", "tlbi vmalle1", "dsb nsh", "isb") };
+}
+
+/// Makes the instruction caches of all CPUs coherent with the data caches.
+///
+/// Like RISC-V, all the CPUs are requested to invalidate their own instruction
+/// caches with inter-processor calls. The data caches are assumed to be coherent
+/// with the instruction caches at the point of unification, which is the case in
+/// QEMU and on the CPUs with `CTR_EL0.IDC` set.
+pub(crate) fn sync_icache_all() {
+    crate::smp::inter_processor_call(&CpuSet::new_full(), sync_icache_local);
+}
+
+fn sync_icache_local() {
+    // SAFETY: Invalidating the instruction cache does not affect memory safety.
+    unsafe { asm!("ic iallu", "dsb nsh", "isb") };
+}
+
+#[derive(Clone, Copy, Pod, Default)]
+#[repr(C)]
+pub struct PageTableEntry(usize);
+
+/// Activate the given level 4 page table.
+///
+/// The root page table is installed in both `TTBR0_EL1` and `TTBR1_EL1`. The
+/// cacheability of the page table walks is set in `TCR_EL1` by the boot code, so
+/// `_root_pt_cache` is ignored.
+///
+/// # Safety
+///
+/// Changing the level 4 page table is unsafe, because it's possible to violate memory safety by
+/// changing the page mapping.
+pub unsafe fn activate_page_table(root_paddr: Paddr, _root_pt_cache: CachePolicy) {
+    assert!(root_paddr % PagingConsts::BASE_PAGE_SIZE == 0);
+    asm!(
+        "dsb ishst",
+        "msr ttbr0_el1, {0}",
+        "msr ttbr1_el1, {0}",
+        "isb",
+        "tlbi vmalle1",
+        "dsb nsh",
+        "isb",
+        in(reg) root_paddr,
+    );
+}
+
+pub fn current_page_table_paddr() -> Paddr {
+    let ttbr0: usize;
+    // SAFETY: Reading `TTBR0_EL1` has no side effects.
+    unsafe { asm!("mrs {}, ttbr0_el1", out(reg) ttbr0, options(nomem, nostack)) };
+    ttbr0 & PageTableEntry::PHYS_ADDR_MASK
+}
+
+impl PageTableEntry {
+    const PHYS_ADDR_MASK: usize = 0x0000_FFFF_FFFF_F000;
+}
+
+/// Parse a bit-flag bits `val` in the representation of `from` to `to` in bits.
+macro_rules! parse_flags {
+    ($val:expr, $from:expr, $to:expr) => {
+        ($val as usize & $from.bits() as usize) >> $from.bits().ilog2() << $to.bits().ilog2()
+    };
+}
+
+impl PageTableEntryTrait for PageTableEntry {
+    fn is_present(&self) -> bool {
+        self.0 & PageTableFlags::VALID.bits() != 0
+    }
+
+    fn new_page(paddr: Paddr, level: PagingLevel, prop: PageProperty) -> Self {
+        // The pages at the last level are marked as pages, and the others are blocks.
+        let mut pte = if level == 1 {
+            Self(paddr & Self::PHYS_ADDR_MASK | PageTableFlags::TABLE_OR_PAGE.bits())
+        } else {
+            Self(paddr & Self::PHYS_ADDR_MASK)
+        };
+        pte.set_prop(prop);
+        pte
+    }
+
+    fn new_pt(paddr: Paddr) -> Self {
+        // The attributes of the table descriptors are not used, so the permissions
+        // are only controlled by the last-level entries.
+        Self(
+            paddr & Self::PHYS_ADDR_MASK
+                | PageTableFlags::VALID.bits()
+                | PageTableFlags::TABLE_OR_PAGE.bits(),
+        )
+    }
+
+    fn paddr(&self) -> Paddr {
+        self.0 & Self::PHYS_ADDR_MASK
+    }
+
+    fn prop(&self) -> PageProperty {
+        let is_user = self.0 & PageTableFlags::USER.bits() != 0;
+        let exec_never = if is_user {
+            PageTableFlags::USER_EXEC_NEVER
+        } else {
+            PageTableFlags::PRIV_EXEC_NEVER
+        };
+
+        let mut flags = PageFlags::R.bits() as usize
+            | parse_flags!(self.0, PageTableFlags::ACCESSED, PageFlags::ACCESSED)
+            | parse_flags!(self.0, PageTableFlags::DIRTY, PageFlags::DIRTY)
+            | parse_flags!(self.0, PageTableFlags::AVAIL1, PageFlags::AVAIL1)
+            | parse_flags!(self.0, PageTableFlags::AVAIL2, PageFlags::AVAIL2);
+        if self.0 & PageTableFlags::READ_ONLY.bits() == 0 {
+            flags |= PageFlags::W.bits() as usize;
+        }
+        if self.0 & exec_never.bits() == 0 {
+            flags |= PageFlags::X.bits() as usize;
+        }
+
+        let mut priv_flags = parse_flags!(self.0, PageTableFlags::USER, PrivFlags::USER);
+        if self.0 & PageTableFlags::NOT_GLOBAL.bits() == 0 {
+            priv_flags |= PrivFlags::GLOBAL.bits() as usize;
+        }
+
+        let cache = match (self.0 & ATTR_INDX_MASK) >> ATTR_INDX_SHIFT {
+            ATTR_INDX_DEVICE => CachePolicy::Uncacheable,
+            ATTR_INDX_NORMAL_NC => CachePolicy::WriteCombining,
+            _ => CachePolicy::Writeback,
+        };
+
+        PageProperty {
+            flags: PageFlags::from_bits(flags as u8).unwrap(),
+            cache,
+            priv_flags: PrivFlags::from_bits(priv_flags as u8).unwrap(),
+        }
+    }
+
+    fn set_prop(&mut self, prop: PageProperty) {
+        let mut flags = PageTableFlags::VALID.bits()
+            | PageTableFlags::ACCESS.bits()
+            | parse_flags!(
+                prop.flags.bits(),
+                PageFlags::ACCESSED,
+                PageTableFlags::ACCESSED
+            )
+            | parse_flags!(prop.flags.bits(), PageFlags::DIRTY, PageTableFlags::DIRTY)
+            | parse_flags!(prop.flags.bits(), PageFlags::AVAIL1, PageTableFlags::AVAIL1)
+            | parse_flags!(prop.flags.bits(), PageFlags::AVAIL2, PageTableFlags::AVAIL2)
+            | parse_flags!(
+                prop.priv_flags.bits(),
+                PrivFlags::USER,
+                PageTableFlags::USER
+            );
+        if !prop.flags.contains(PageFlags::W) {
+            flags |= PageTableFlags::READ_ONLY.bits();
+        }
+        if !prop.priv_flags.contains(PrivFlags::GLOBAL) {
+            flags |= PageTableFlags::NOT_GLOBAL.bits();
+        }
+
+        // The user pages are never executable in the kernel mode, and the kernel pages
+        // are never executable in the user mode.
+        if prop.priv_flags.contains(PrivFlags::USER) {
+            flags |= PageTableFlags::PRIV_EXEC_NEVER.bits();
+            if !prop.flags.contains(PageFlags::X) {
+                flags |= PageTableFlags::USER_EXEC_NEVER.bits();
+            }
+        } else {
+            flags |= PageTableFlags::USER_EXEC_NEVER.bits();
+            if !prop.flags.contains(PageFlags::X) {
+                flags |= PageTableFlags::PRIV_EXEC_NEVER.bits();
+            }
+        }
+
+        match prop.cache {
+            CachePolicy::Writeback => {
+                flags |=
+                    ATTR_INDX_NORMAL << ATTR_INDX_SHIFT | PageTableFlags::INNER_SHAREABLE.bits();
+            }
+            CachePolicy::Uncacheable => {
+                // Currently, Asterinas uses `Uncacheable` for I/O memory.
+                flags |= ATTR_INDX_DEVICE << ATTR_INDX_SHIFT
+                    | PageTableFlags::PRIV_EXEC_NEVER.bits()
+                    | PageTableFlags::USER_EXEC_NEVER.bits();
+            }
+            CachePolicy::WriteCombining => {
+                flags |=
+                    ATTR_INDX_NORMAL_NC << ATTR_INDX_SHIFT | PageTableFlags::INNER_SHAREABLE.bits();
+            }
+            _ => panic!("unsupported cache policy"),
+        }
+
+        self.0 = (self.0 & (Self::PHYS_ADDR_MASK | PageTableFlags::TABLE_OR_PAGE.bits())) | flags;
+    }
+
+    fn is_last(&self, level: PagingLevel) -> bool {
+        level == 1 || self.0 & PageTableFlags::TABLE_OR_PAGE.bits() == 0
+    }
+}
+
+impl fmt::Debug for PageTableEntry {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let mut f = f.debug_struct("PageTableEntry");
+        f.field("raw", &format_args!("{:#x}", self.0))
+            .field("paddr", &format_args!("{:#x}", self.paddr()))
+            .field("present", &self.is_present())
+            .field(
+                "flags",
+                &PageTableFlags::from_bits_truncate(self.0 & !Self::PHYS_ADDR_MASK),
+            )
+            .field("prop", &self.prop())
+            .finish()
+    }
+}
+
+pub(crate) fn __memcpy_fallible(dst: *mut u8, src: *const u8, size: usize) -> usize {
+    // TODO: implement fallible
+    unsafe { core::ptr::copy(src, dst, size) };
+    0
+}
+
+pub(crate) fn __memset_fallible(dst: *mut u8, value: u8, size: usize) -> usize {
+    // TODO: implement fallible
+    unsafe { core::ptr::write_bytes(dst, value, size) };
+    0
+}
@@ -0,0 +1,124 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! Platform-specific code for the AArch64 platform.
+//!
+//! The supported platform is the `virt` machine of QEMU with a GICv3 interrupt
+//! controller. The kernel runs at EL1, the CPUs are managed with the PSCI
+//! firmware interface, and the devices are discovered from the device tree.
+
+pub mod boot;
+pub(crate) mod cpu;
+pub mod device;
+pub(crate) mod freq;
+pub(crate) mod gic;
+pub(crate) mod idle;
+pub mod iommu;
+pub(crate) mod irq;
+pub(crate) mod mm;
+pub(crate) mod pci;
+pub(crate) mod power;
+pub(crate) mod psci;
+pub mod qemu;
+pub mod serial;
+pub mod task;
+pub mod timer;
+pub mod trap;
+
+use core::sync::atomic::Ordering;
+
+use crate::{cpu::PinCurrentCpu, mm::numa::NumaTopology};
+
+#[cfg(feature = "cvm_guest")]
+pub(crate) fn init_cvm_guest() {
+    // Unimplemented, no-op
+}
+
+pub(crate) fn init_firmware_tables() {
+    // Unimplemented, no-op
+}
+
+pub(crate) fn numa_topology() -> Option<NumaTopology> {
+    // Unimplemented, no NUMA topology
+    None
+}
+
+pub(crate) fn init_on_bsp() {
+    // SAFETY: this function is only called once on BSP.
+    unsafe {
+        trap::init(true);
+    }
+    irq::init();
+    psci::init();
+
+    // SAFETY: they are only called once on BSP and ACPI has been initialized.
+    unsafe {
+        crate::cpu::init_num_cpus();
+        crate::cpu::set_this_cpu_id(0);
+    }
+
+    // SAFETY: no CPU local objects have been accessed by this far. And
+    // we are on the BSP.
+    unsafe { crate::cpu::local::init_on_bsp() };
+
+    gic::init();
+    serial::callback_init();
+
+    crate::boot::smp::boot_all_aps();
+
+    timer::init();
+}
+
+/// Architecture-specific initialization on the application processor.
+///
+/// # Safety
+///
+/// This function must be called only once on each application processor.
+/// And it should be called after the BSP's call to [`init_on_bsp`].
+pub(crate) unsafe fn init_on_ap() {
+    let cpu_id = crate::trap::disable_local().current_cpu();
+    gic::init_on_ap(cpu_id);
+}
+
+/// Completes the interrupt acknowledged from the GIC, if any.
+///
+/// The IPIs are completed before their handlers are called, so that no IPI sent
+/// during the handling is missed.
+pub(crate) fn interrupts_ack(_irq_number: usize) {
+    gic::complete();
+}
+
+/// Return the frequency of TSC. The unit is Hz.
+///
+/// The virtual count of the generic timer is used as the TSC.
+pub fn tsc_freq() -> u64 {
+    timer::TIMER_COUNTER_FREQ.load(Ordering::Relaxed)
+}
+
+/// Reads the current value of the processor’s time-stamp counter (TSC).
+pub fn read_tsc() -> u64 {
+    let cntvct: u64;
+    // SAFETY: Reading the virtual count has no side effects. The `isb` prevents the
+    // count from being read speculatively.
+    unsafe { core::arch::asm!("isb", "mrs {}, cntvct_el0", out(reg) cntvct, options(nostack)) };
+    cntvct
+}
+
+pub(crate) fn enable_cpu_features() {
+    // The kernel is built with the soft-float ABI and does not use the FP/SIMD registers
+    // itself, but the user space does, and the registers are saved and restored by the
+    // kernel on context switches.
+    const CPACR_EL1_FPEN: u64 = 0b11 << 20;
+
+    // SAFETY: Enabling the accesses to the FP/SIMD registers does not affect memory safety.
+    unsafe {
+        core::arch::asm!(
+            "mrs {tmp}, cpacr_el1",
+            "orr {tmp}, {tmp}, {fpen}",
+            "msr cpacr_el1, {tmp}",
+            "isb",
+            tmp = out(reg) _,
+            fpen = in(reg) CPACR_EL1_FPEN,
+            options(nostack),
+        );
+    }
+}
@@ -0,0 +1,32 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! Turning off and restarting the AArch64 machine with the PSCI.
+
+use super::psci;
+
+pub(crate) fn poweroff() -> ! {
+    super::irq::disable_local();
+
+    psci::system_off();
+
+    log::warn!("Failed to turn off the machine, halting");
+    halt()
+}
+
+pub(crate) fn restart() -> ! {
+    super::irq::disable_local();
+
+    psci::system_reset();
+
+    log::warn!("Failed to restart the machine, halting");
+    halt()
+}
+
+pub(crate) fn halt() -> ! {
+    super::irq::disable_local();
+
+    loop {
+        // SAFETY: Waiting for interrupts does not affect memory safety.
+        unsafe { core::arch::asm!("wfi") };
+    }
+}
@@ -0,0 +1,94 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! The Power State Coordination Interface (PSCI).
+//!
+//! The PSCI firmware (or the hypervisor) turns on the secondary CPUs and turns
+//! off or resets the machine. It is called with the `HVC` or the `SMC` instruction,
+//! which is specified by the `method` property of the `/psci` node in the device
+//! tree.
+//!
+//! Ref: <https://developer.arm.com/documentation/den0022/latest>
+
+use core::sync::atomic::{AtomicBool, Ordering};
+
+use super::boot::DEVICE_TREE;
+
+const CPU_ON: u32 = 0xC400_0003;
+const SYSTEM_OFF: u32 = 0x8400_0008;
+const SYSTEM_RESET: u32 = 0x8400_0009;
+
+/// Whether the PSCI is called with `SMC` rather than `HVC`.
+static USE_SMC: AtomicBool = AtomicBool::new(false);
+
+/// An error returned by the PSCI.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct PsciError(i32);
+
+/// Initializes the PSCI with the conduit in the device tree.
+pub(super) fn init() {
+    let method = DEVICE_TREE
+        .get()
+        .unwrap()
+        .find_node("/psci")
+        .and_then(|psci| psci.property("method"))
+        .and_then(|method| method.as_str());
+    match method {
+        Some("smc") => USE_SMC.store(true, Ordering::Relaxed),
+        Some("hvc") => {}
+        method => log::warn!("[PSCI]: Unknown conduit {:?}, using HVC", method),
+    }
+}
+
+/// Turns on a CPU, which starts at `entry_paddr` with `context_id` in `x0`.
+///
+/// The CPU starts with the MMU off, so the entry point must be a physical address.
+pub(crate) fn cpu_on(mpidr: u64, entry_paddr: usize, context_id: usize) -> Result<(), PsciError> {
+    let ret = call(CPU_ON, mpidr as usize, entry_paddr, context_id);
+    if ret == 0 {
+        Ok(())
+    } else {
+        Err(PsciError(ret as i32))
+    }
+}
+
+/// Turns off the machine.
+///
+/// This function returns only if the call fails.
+pub(crate) fn system_off() {
+    call(SYSTEM_OFF, 0, 0, 0);
+}
+
+/// Resets the machine.
+///
+/// This function returns only if the call fails.
+pub(crate) fn system_reset() {
+    call(SYSTEM_RESET, 0, 0, 0);
+}
+
+fn call(function_id: u32, arg0: usize, arg1: usize, arg2: usize) -> isize {
+    let mut ret = function_id as usize;
+    // SAFETY: The PSCI calls follow the SMC calling convention, which only modifies the
+    // registers that are caller-saved in the C ABI.
+    unsafe {
+        if USE_SMC.load(Ordering::Relaxed) {
+            core::arch::asm!(
+                "smc #0",
+                inout("x0") ret,
+                in("x1") arg0,
+                in("x2") arg1,
+                in("x3") arg2,
+                clobber_abi("C"),
+            );
+        } else {
+            core::arch::asm!(
+                "hvc #0",
+                inout("x0") ret,
+                in("x1") arg0,
+                in("x2") arg1,
+                in("x3") arg2,
+                clobber_abi("C"),
+            );
+        }
+    }
+    ret as isize
+}
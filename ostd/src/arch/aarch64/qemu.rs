@@ -0,0 +1,25 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! Providing the ability to exit QEMU and return a value as debug result.
+
+/// The exit code of QEMU.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum QemuExitCode {
+    /// The code that indicates a successful exit.
+    Success,
+    /// The code that indicates a failed exit.
+    Failed,
+}
+
+/// Exit QEMU with the given exit code.
+///
+/// The PSCI `SYSTEM_OFF` call does not carry an exit code, so QEMU always exits
+/// with zero. The result is reported in the log instead.
+pub fn exit_qemu(exit_code: QemuExitCode) -> ! {
+    log::debug!("exit qemu with exit code {exit_code:?}");
+    if exit_code == QemuExitCode::Failed {
+        crate::early_println!("[ostd] QEMU is exiting with a failure");
+    }
+    super::psci::system_off();
+    unreachable!("qemu does not exit");
+}
@@ -0,0 +1,160 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! The console I/O.
+//!
+//! The console is the PL011 UART, whose registers are accessed through the linear
+//! mapping, so that it can be used before the kernel page table is activated.
+
+use alloc::{fmt, sync::Arc, vec::Vec};
+use core::{
+    fmt::Write,
+    sync::atomic::{AtomicUsize, Ordering},
+};
+
+use spin::Once;
+
+use super::{
+    boot::DEVICE_TREE,
+    gic::{self, GIC},
+};
+use crate::{
+    mm::{kspace::LINEAR_MAPPING_BASE_VADDR, paddr_to_vaddr},
+    sync::SpinLock,
+    trap::{IrqLine, TrapFrame},
+};
+
+/// Prints the formatted arguments to the standard output using the serial port.
+#[inline]
+pub fn print(args: fmt::Arguments) {
+    Stdout.write_fmt(args).unwrap();
+}
+
+/// The callback function for console input.
+pub type InputCallback = dyn Fn(u8) + Send + Sync + 'static;
+
+/// Registers a callback function to be called when there is console input.
+pub fn register_console_input_callback(f: &'static InputCallback) {
+    SERIAL_INPUT_CALLBACKS
+        .disable_irq()
+        .lock()
+        .push(Arc::new(f));
+}
+
+struct Stdout;
+
+impl Write for Stdout {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        for &c in s.as_bytes() {
+            send(c);
+        }
+        Ok(())
+    }
+}
+
+/// The physical address of the PL011 UART on the QEMU `virt` machine.
+const DEFAULT_PL011_PADDR: usize = 0x0900_0000;
+
+/// The data register.
+const UARTDR: usize = 0x00;
+/// The flag register.
+const UARTFR: usize = 0x18;
+/// The interrupt mask set/clear register.
+const UARTIMSC: usize = 0x38;
+/// The interrupt clear register.
+const UARTICR: usize = 0x44;
+
+/// The receive FIFO is empty.
+const UARTFR_RXFE: u32 = 1 << 4;
+/// The transmit FIFO is full.
+const UARTFR_TXFF: u32 = 1 << 5;
+/// The receive interrupt.
+const UART_RXI: u32 = 1 << 4;
+
+/// The virtual address of the PL011 registers.
+///
+/// It points to the UART of the QEMU `virt` machine until the serial port is
+/// initialized, so that the early prints work.
+static PL011_BASE: AtomicUsize = AtomicUsize::new(LINEAR_MAPPING_BASE_VADDR + DEFAULT_PL011_PADDR);
+
+static CONSOLE_IRQ_CALLBACK: Once<SpinLock<IrqLine>> = Once::new();
+static SERIAL_INPUT_CALLBACKS: SpinLock<Vec<Arc<InputCallback>>> = SpinLock::new(Vec::new());
+
+/// Initializes the serial port.
+pub(crate) fn init() {
+    let paddr = DEVICE_TREE
+        .get()
+        .and_then(|device_tree| device_tree.find_compatible(&["arm,pl011"]))
+        .and_then(|node| node.reg())
+        .and_then(|mut regions| regions.next())
+        .map_or(DEFAULT_PL011_PADDR, |region| {
+            region.starting_address as usize
+        });
+    PL011_BASE.store(paddr_to_vaddr(paddr), Ordering::Relaxed);
+}
+
+pub(crate) fn callback_init() {
+    let Some(intid) = DEVICE_TREE
+        .get()
+        .unwrap()
+        .find_compatible(&["arm,pl011"])
+        .and_then(|node| node.property("interrupts"))
+        .and_then(|interrupts| gic::parse_interrupt(interrupts.value, 0))
+    else {
+        log::warn!("No interrupt is found for the serial port");
+        return;
+    };
+
+    let mut irq = IrqLine::alloc().unwrap();
+    irq.on_active(handle_serial_input);
+    GIC.get().unwrap().enable(intid, irq.clone()).unwrap();
+    CONSOLE_IRQ_CALLBACK.call_once(|| SpinLock::new(irq));
+
+    // SAFETY: Enabling the receive interrupt of the UART does not affect memory safety.
+    unsafe { write_reg(UARTIMSC, UART_RXI) };
+}
+
+fn handle_serial_input(_trap_frame: &TrapFrame) {
+    let Some(lock) = SERIAL_INPUT_CALLBACKS.try_lock() else {
+        // SAFETY: Clearing the interrupts of the UART does not affect memory safety.
+        unsafe { write_reg(UARTICR, UART_RXI) };
+        return;
+    };
+    while let Some(received_char) = receive_char() {
+        for callback in lock.iter() {
+            callback(received_char);
+        }
+    }
+}
+
+/// Sends a byte on the serial port.
+pub fn send(data: u8) {
+    // SAFETY: The registers are the MMIO registers of the UART.
+    unsafe {
+        while read_reg(UARTFR) & UARTFR_TXFF != 0 {
+            core::hint::spin_loop();
+        }
+        write_reg(UARTDR, data as u32);
+    }
+}
+
+/// Receives a byte on the serial port. non-blocking
+pub fn receive_char() -> Option<u8> {
+    // SAFETY: The registers are the MMIO registers of the UART. Reading the data
+    // register pops a byte from the receive FIFO, which also clears the receive
+    // interrupt if the FIFO becomes empty.
+    unsafe {
+        if read_reg(UARTFR) & UARTFR_RXFE != 0 {
+            None
+        } else {
+            Some(read_reg(UARTDR) as u8)
+        }
+    }
+}
+
+unsafe fn read_reg(offset: usize) -> u32 {
+    ((PL011_BASE.load(Ordering::Relaxed) + offset) as *const u32).read_volatile()
+}
+
+unsafe fn write_reg(offset: usize, value: u32) {
+    ((PL011_BASE.load(Ordering::Relaxed) + offset) as *mut u32).write_volatile(value)
+}
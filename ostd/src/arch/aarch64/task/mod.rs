@@ -0,0 +1,100 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! The architecture support of context switch.
+
+use crate::task::TaskContextApi;
+
+core::arch::global_asm!(include_str!("switch.S"));
+
+#[derive(Debug, Default, Clone, Copy)]
+#[repr(C)]
+pub(crate) struct TaskContext {
+    pub regs: CalleeRegs,
+    pub pc: usize,
+}
+
+/// Callee-saved registers.
+#[derive(Debug, Default, Clone, Copy)]
+#[repr(C)]
+pub struct CalleeRegs {
+    /// sp
+    pub sp: u64,
+    /// x19
+    pub x19: u64,
+    /// x20
+    pub x20: u64,
+    /// x21
+    pub x21: u64,
+    /// x22
+    pub x22: u64,
+    /// x23
+    pub x23: u64,
+    /// x24
+    pub x24: u64,
+    /// x25
+    pub x25: u64,
+    /// x26
+    pub x26: u64,
+    /// x27
+    pub x27: u64,
+    /// x28
+    pub x28: u64,
+    /// x29 (the frame pointer)
+    pub x29: u64,
+}
+
+impl CalleeRegs {
+    /// Creates new `CalleeRegs`
+    pub const fn new() -> Self {
+        CalleeRegs {
+            sp: 0,
+            x19: 0,
+            x20: 0,
+            x21: 0,
+            x22: 0,
+            x23: 0,
+            x24: 0,
+            x25: 0,
+            x26: 0,
+            x27: 0,
+            x28: 0,
+            x29: 0,
+        }
+    }
+}
+
+impl TaskContext {
+    pub const fn new() -> Self {
+        TaskContext {
+            regs: CalleeRegs::new(),
+            pc: 0,
+        }
+    }
+
+    /// Gets the frame pointer saved when the task is switched out.
+    pub(crate) fn frame_pointer(&self) -> usize {
+        self.regs.x29 as usize
+    }
+}
+
+impl TaskContextApi for TaskContext {
+    fn set_instruction_pointer(&mut self, ip: usize) {
+        self.pc = ip;
+    }
+
+    fn instruction_pointer(&self) -> usize {
+        self.pc
+    }
+
+    fn set_stack_pointer(&mut self, sp: usize) {
+        self.regs.sp = sp as u64;
+    }
+
+    fn stack_pointer(&self) -> usize {
+        self.regs.sp as usize
+    }
+}
+
+extern "C" {
+    pub(crate) fn context_switch(cur: *mut TaskContext, nxt: *const TaskContext);
+}
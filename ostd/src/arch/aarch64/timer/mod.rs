@@ -0,0 +1,227 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! The timer support.
+//!
+//! Each CPU has a virtual timer of the generic timer, which interrupts when the
+//! virtual count reaches the programmed compare value. Like the TSC deadline mode
+//! on x86, the deadline is the earlier one of the next system tick, which is only
+//! handled by the BSP, and the expiration of the high-resolution timers on the CPU.
+
+use core::{
+    arch::asm,
+    sync::atomic::{AtomicU32, AtomicU64, Ordering},
+};
+
+use spin::Once;
+
+use super::gic;
+use crate::{
+    arch::boot::DEVICE_TREE,
+    cpu::{CpuId, PinCurrentCpu},
+    io_mem::IoMem,
+    mm::page_prop::{CachePolicy, PageFlags},
+    timer::{hrtimer, INTERRUPT_CALLBACKS},
+    trap::{self, DisabledLocalIrqGuard, IrqLine, TrapFrame},
+};
+
+/// The timer frequency (Hz). Here we choose 1000Hz since 1000Hz is easier for unit conversion and
+/// convenient for timer. What's more, the frequency cannot be set too high or too low, 1000Hz is
+/// a modest choice.
+///
+/// For system performance reasons, this rate cannot be set too high, otherwise most of the time
+/// is spent executing timer code.
+pub const TIMER_FREQ: u64 = 1000;
+
+/// The frequency of the system counter (Hz).
+pub(crate) static TIMER_COUNTER_FREQ: AtomicU64 = AtomicU64::new(1);
+
+/// [`IoMem`] of PL031 RTC, which will be used by `aster-time`.
+pub static PL031_IO_MEM: Once<IoMem> = Once::new();
+
+/// The INTID of the virtual timer interrupts.
+static TIMER_INTID: AtomicU32 = AtomicU32::new(DEFAULT_TIMER_INTID);
+
+/// The INTID of the virtual timer interrupts on the QEMU `virt` machine.
+const DEFAULT_TIMER_INTID: u32 = 27;
+
+/// Enables the timer (`CNTV_CTL_EL0.ENABLE`).
+const CNTV_CTL_ENABLE: u64 = 1 << 0;
+
+static TIMER_IRQ: Once<IrqLine> = Once::new();
+
+/// The virtual count of the next system tick, which is only handled by the BSP.
+static NEXT_TICK_TIME: AtomicU64 = AtomicU64::new(0);
+
+/// The virtual count before which the tick of the BSP is stopped, or zero if the
+/// tick is not stopped.
+static TICK_STOPPED_UNTIL: AtomicU64 = AtomicU64::new(0);
+
+pub(super) fn init() {
+    let timer_freq: u64;
+    // SAFETY: Reading `CNTFRQ_EL0` has no side effects.
+    unsafe { asm!("mrs {}, cntfrq_el0", out(reg) timer_freq, options(nomem, nostack)) };
+    TIMER_COUNTER_FREQ.store(timer_freq, Ordering::Relaxed);
+
+    let device_tree = DEVICE_TREE.get().unwrap();
+    // The interrupts of the generic timer are the secure physical timer, the non-secure
+    // physical timer, the virtual timer and the hypervisor timer, in order.
+    if let Some(intid) = device_tree
+        .find_compatible(&["arm,armv8-timer"])
+        .and_then(|node| node.property("interrupts"))
+        .and_then(|interrupts| gic::parse_interrupt(interrupts.value, 2))
+    {
+        TIMER_INTID.store(intid, Ordering::Relaxed);
+    }
+
+    if let Some(node) = device_tree.find_compatible(&["arm,pl031"]) {
+        let region = node.reg().unwrap().next().unwrap();
+        // SAFETY: The range is the MMIO registers of the PL031 RTC.
+        let io_mem = unsafe {
+            IoMem::new(
+                (region.starting_address as usize)
+                    ..(region.starting_address as usize) + region.size.unwrap(),
+                PageFlags::RW,
+                CachePolicy::Uncacheable,
+            )
+        };
+        PL031_IO_MEM.call_once(|| io_mem);
+    }
+
+    let mut timer_irq = IrqLine::alloc().unwrap();
+    timer_irq.on_active(timer_callback);
+    TIMER_IRQ.call_once(|| timer_irq);
+
+    NEXT_TICK_TIME.store(super::read_tsc() + time_step(), Ordering::Relaxed);
+    program_timer(true, None);
+    enable_timer_interrupt();
+}
+
+/// Initializes the timer of the current AP.
+///
+/// The timer is not armed until a high-resolution timer is armed on the AP.
+pub(crate) fn init_on_ap() {
+    TIMER_IRQ.wait();
+    program_timer(false, None);
+    enable_timer_interrupt();
+}
+
+/// Programs the timer of the current CPU for the earliest expiration of the
+/// high-resolution timers on the CPU.
+pub(crate) fn program_next_event(guard: &DisabledLocalIrqGuard) {
+    let is_bsp = guard.current_cpu() == CpuId::bsp();
+    program_timer(is_bsp, hrtimer::next_expiration(guard));
+}
+
+/// Stops the tick of the current CPU until `until_ns`.
+///
+/// Only the BSP has a tick.
+pub(crate) fn stop_tick(guard: &DisabledLocalIrqGuard, until_ns: u64) {
+    if guard.current_cpu() == CpuId::bsp() {
+        TICK_STOPPED_UNTIL.store(ns_to_time(until_ns).max(1), Ordering::Relaxed);
+        program_next_event(guard);
+    }
+}
+
+/// Restarts the tick of the current CPU.
+///
+/// The ticks missed while the tick is stopped are accounted on the next timer
+/// interrupt, which arrives at once if any tick is missed.
+pub(crate) fn restart_tick(guard: &DisabledLocalIrqGuard) {
+    if guard.current_cpu() == CpuId::bsp() {
+        TICK_STOPPED_UNTIL.store(0, Ordering::Relaxed);
+        program_next_event(guard);
+    }
+}
+
+fn timer_callback(trap_frame: &TrapFrame) {
+    let irq_guard = trap::disable_local();
+    let is_bsp = irq_guard.current_cpu() == CpuId::bsp();
+
+    let nr_ticks = nr_due_ticks(is_bsp);
+    if nr_ticks > 0 {
+        crate::timer::jiffies::ELAPSED.fetch_add(nr_ticks, Ordering::SeqCst);
+
+        let callbacks_guard = INTERRUPT_CALLBACKS.get_with(&irq_guard);
+        for callback in callbacks_guard.borrow().iter() {
+            (callback)();
+        }
+        drop(callbacks_guard);
+
+        crate::timer::call_sample_callback(trap_frame);
+        crate::task::watchdog::check(trap_frame);
+        crate::sync::rcu::pass_quiescent_state_on_tick();
+    }
+
+    hrtimer::expire_timers(&irq_guard);
+    // Programming the timer also deasserts the timer interrupt, which is level-sensitive.
+    program_next_event(&irq_guard);
+}
+
+/// Returns the number of system ticks that are due on a timer interrupt.
+///
+/// The ticks missed because of a late interrupt or a stopped tick are all counted,
+/// and the time of the next tick is advanced accordingly.
+fn nr_due_ticks(is_bsp: bool) -> u64 {
+    if !is_bsp {
+        return 0;
+    }
+
+    let now = super::read_tsc();
+    let next_tick = NEXT_TICK_TIME.load(Ordering::Relaxed);
+    if now < next_tick || now < TICK_STOPPED_UNTIL.load(Ordering::Relaxed) {
+        return 0;
+    }
+    let nr_ticks = (now - next_tick) / time_step() + 1;
+    NEXT_TICK_TIME.store(next_tick + nr_ticks * time_step(), Ordering::Relaxed);
+    nr_ticks
+}
+
+/// Programs the timer deadline of the current CPU.
+///
+/// The deadline is the earlier one of the next system tick, if on the BSP, and
+/// `next_expiration_ns`.
+fn program_timer(is_bsp: bool, next_expiration_ns: Option<u64>) {
+    let next_tick = if is_bsp {
+        NEXT_TICK_TIME
+            .load(Ordering::Relaxed)
+            .max(TICK_STOPPED_UNTIL.load(Ordering::Relaxed))
+    } else {
+        u64::MAX
+    };
+    let next_expiration = next_expiration_ns.map_or(u64::MAX, ns_to_time);
+    let deadline = next_tick.min(next_expiration);
+
+    // A deadline that never arrives disarms the timer.
+    let ctl = if deadline == u64::MAX {
+        0
+    } else {
+        CNTV_CTL_ENABLE
+    };
+    // SAFETY: Programming the virtual timer does not affect memory safety.
+    unsafe {
+        asm!(
+            "msr cntv_cval_el0, {cval}",
+            "msr cntv_ctl_el0, {ctl}",
+            "isb",
+            cval = in(reg) deadline,
+            ctl = in(reg) ctl,
+            options(nomem, nostack),
+        );
+    }
+}
+
+fn enable_timer_interrupt() {
+    gic::enable_local_ppi(
+        TIMER_INTID.load(Ordering::Relaxed),
+        TIMER_IRQ.get().unwrap().num(),
+    );
+}
+
+fn time_step() -> u64 {
+    TIMER_COUNTER_FREQ.load(Ordering::Relaxed) / TIMER_FREQ
+}
+
+fn ns_to_time(ns: u64) -> u64 {
+    let time = ns as u128 * TIMER_COUNTER_FREQ.load(Ordering::Relaxed) as u128 / 1_000_000_000;
+    time.try_into().unwrap_or(u64::MAX)
+}
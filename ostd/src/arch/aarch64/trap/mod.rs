@@ -0,0 +1,139 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! Handles trap.
+
+mod trap;
+
+use core::{mem::size_of, ops::Range};
+
+pub use trap::{GeneralRegs, TrapFrame, TrapKind, UserContext};
+
+use super::{gic, irq};
+use crate::{
+    cpu_local_cell, mm::Vaddr, task::current_kernel_stack, trap::call_irq_callback_functions,
+};
+
+cpu_local_cell! {
+    static IS_KERNEL_INTERRUPTED: bool = false;
+}
+
+/// Initialize interrupt handling on AArch64.
+pub unsafe fn init(on_bsp: bool) {
+    self::trap::init();
+}
+
+/// Returns true if this function is called within the context of an IRQ handler
+/// and the IRQ occurs while the CPU is executing in the kernel mode.
+/// Otherwise, it returns false.
+pub fn is_kernel_interrupted() -> bool {
+    IS_KERNEL_INTERRUPTED.load()
+}
+
+/// Walks the call chain of the kernel code that is interrupted with the trap frame.
+///
+/// The visitor is called with the interrupted instruction pointer first, and then
+/// with the return address of each caller, until it returns `false` or the chain
+/// ends. The callers are found by following the frame pointers, so the call chain
+/// is complete only if the kernel is built with frame pointers. The frame pointers
+/// are followed only within the kernel stack of the current task.
+pub fn walk_kernel_stack(f: &TrapFrame, mut visit: impl FnMut(Vaddr) -> bool) {
+    if !visit(f.elr) {
+        return;
+    }
+    let Some(stack) = current_kernel_stack() else {
+        return;
+    };
+    walk_frame_pointers(f.general.x29, stack, visit);
+}
+
+/// Walks the call chain by following the frame pointers from `frame_pointer`.
+///
+/// The visitor is called with the return address of each caller, until it returns
+/// `false` or the chain ends. Only the frames within `stack` are followed.
+pub(crate) fn walk_frame_pointers(
+    mut frame_pointer: usize,
+    stack: Range<Vaddr>,
+    mut visit: impl FnMut(Vaddr) -> bool,
+) {
+    // Each frame pointer points to a frame record, which consists of the caller's
+    // frame pointer and the return address.
+    while frame_pointer % size_of::<usize>() == 0
+        && stack.start <= frame_pointer
+        && frame_pointer + 2 * size_of::<usize>() <= stack.end
+    {
+        // SAFETY: The two words are within the kernel stack, which is mapped. The stack
+        // may be modified concurrently if it is not the interrupted one, so the words
+        // are read as volatile and are only used as hints.
+        let (caller_frame_pointer, return_address) = unsafe {
+            let frame = frame_pointer as *const usize;
+            (frame.read_volatile(), frame.add(1).read_volatile())
+        };
+        if return_address == 0 || !visit(return_address) {
+            return;
+        }
+        // The stack grows downwards, so the frames of the callers are at higher addresses.
+        if caller_frame_pointer <= frame_pointer {
+            return;
+        }
+        frame_pointer = caller_frame_pointer;
+    }
+}
+
+/// Handles the IRQs from either the kernel or the user space.
+///
+/// All the pending interrupts are acknowledged from the GIC and handled in turn.
+pub(super) fn handle_irq(trap_frame: &TrapFrame) {
+    while let Some(intid) = gic::ack() {
+        if intid == gic::IPI_SGI {
+            irq::handle_ipi(trap_frame);
+        } else if let Some(irq_num) = gic::irq_num_of(intid) {
+            call_irq_callback_functions(trap_frame, irq_num as usize);
+        } else {
+            log::warn!("Unexpected interrupt: {}", intid);
+            gic::complete();
+        }
+    }
+}
+
+/// Handle traps (only from kernel).
+#[no_mangle]
+extern "C" fn trap_handler(f: &mut TrapFrame, kind: usize) {
+    match TrapKind::from_raw(kind) {
+        TrapKind::Irq => {
+            IS_KERNEL_INTERRUPTED.store(true);
+            handle_irq(f);
+            IS_KERNEL_INTERRUPTED.store(false);
+        }
+        kind => {
+            let (esr, far) = read_esr_and_far();
+            panic!(
+                "Cannot handle kernel cpu exception: {kind:?}. esr: {esr:#x}, far: {far:#x}, trapframe: {f:#x?}.",
+            );
+        }
+    }
+}
+
+/// Handle the exceptions that are never expected, e.g., the ones from the AArch32 user space.
+#[no_mangle]
+extern "C" fn trap_unexpected_handler(kind: usize, esr: usize, elr: usize) -> ! {
+    panic!(
+        "Unexpected exception: {:?}. esr: {esr:#x}, elr: {elr:#x}.",
+        TrapKind::from_raw(kind)
+    );
+}
+
+/// Reads the syndrome and the faulting address of the last synchronous exception.
+pub(super) fn read_esr_and_far() -> (usize, usize) {
+    let (esr, far): (usize, usize);
+    // SAFETY: Reading `ESR_EL1` and `FAR_EL1` has no side effects.
+    unsafe {
+        core::arch::asm!(
+            "mrs {esr}, esr_el1",
+            "mrs {far}, far_el1",
+            esr = out(reg) esr,
+            far = out(reg) far,
+            options(nomem, nostack),
+        );
+    }
+    (esr, far)
+}
@@ -0,0 +1,190 @@
+// SPDX-License-Identifier: MPL-2.0
+
+use core::arch::{asm, global_asm};
+
+use crate::Pod;
+
+global_asm!(include_str!("trap.S"));
+
+/// Initializes interrupt handling for the current CPU.
+///
+/// # Safety
+///
+/// This function will set `VBAR_EL1` to the internal exception vector table.
+///
+/// You **MUST NOT** modify this register later.
+pub unsafe fn init() {
+    asm!(
+        "msr vbar_el1, {}",
+        "isb",
+        in(reg) trap_vector_table as usize,
+        options(nostack),
+    );
+}
+
+/// The kind of an exception, which is determined by the entry of the exception
+/// vector table.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[repr(usize)]
+pub enum TrapKind {
+    /// Synchronous exceptions, including the system calls.
+    Sync = 0,
+    /// IRQ interrupts.
+    Irq = 1,
+    /// FIQ interrupts.
+    Fiq = 2,
+    /// System errors.
+    SError = 3,
+}
+
+impl TrapKind {
+    pub(super) fn from_raw(raw: usize) -> Self {
+        match raw {
+            0 => Self::Sync,
+            1 => Self::Irq,
+            2 => Self::Fiq,
+            _ => Self::SError,
+        }
+    }
+}
+
+/// Trap frame of kernel interrupt
+///
+/// # Trap handler
+///
+/// You need to define a handler function like this:
+///
+/// ```no_run
+/// #[no_mangle]
+/// pub extern "C" fn trap_handler(tf: &mut TrapFrame, kind: usize) {
+///     println!("TRAP! tf: {:#x?}", tf);
+/// }
+/// ```
+#[derive(Debug, Default, Clone, Copy)]
+#[repr(C)]
+pub struct TrapFrame {
+    /// General registers
+    pub general: GeneralRegs,
+    /// Exception Link Register
+    pub elr: usize,
+    /// Saved Program Status Register
+    pub spsr: usize,
+}
+
+/// Saved registers on a trap.
+#[derive(Debug, Default, Clone, Copy, Pod)]
+#[repr(C)]
+pub struct UserContext {
+    /// General registers
+    pub general: GeneralRegs,
+    /// Exception Link Register
+    pub elr: usize,
+    /// Saved Program Status Register
+    pub spsr: usize,
+    /// Thread ID Register (`TPIDR_EL0`)
+    pub tpidr: usize,
+}
+
+impl UserContext {
+    /// Go to user space with the context, and come back when a trap occurs.
+    ///
+    /// On return, the context will be reset to the status before the trap.
+    /// The kind of the trap will be returned.
+    pub fn run(&mut self) -> TrapKind {
+        TrapKind::from_raw(unsafe { run_user(self) })
+    }
+}
+
+/// General registers
+#[derive(Debug, Default, Clone, Copy, Pod)]
+#[repr(C)]
+#[allow(missing_docs)]
+pub struct GeneralRegs {
+    pub x0: usize,
+    pub x1: usize,
+    pub x2: usize,
+    pub x3: usize,
+    pub x4: usize,
+    pub x5: usize,
+    pub x6: usize,
+    pub x7: usize,
+    pub x8: usize,
+    pub x9: usize,
+    pub x10: usize,
+    pub x11: usize,
+    pub x12: usize,
+    pub x13: usize,
+    pub x14: usize,
+    pub x15: usize,
+    pub x16: usize,
+    pub x17: usize,
+    pub x18: usize,
+    pub x19: usize,
+    pub x20: usize,
+    pub x21: usize,
+    pub x22: usize,
+    pub x23: usize,
+    pub x24: usize,
+    pub x25: usize,
+    pub x26: usize,
+    pub x27: usize,
+    pub x28: usize,
+    pub x29: usize,
+    pub x30: usize,
+    pub sp: usize,
+}
+
+impl UserContext {
+    /// Get number of syscall
+    pub fn get_syscall_num(&self) -> usize {
+        self.general.x8
+    }
+
+    /// Get return value of syscall
+    pub fn get_syscall_ret(&self) -> usize {
+        self.general.x0
+    }
+
+    /// Set return value of syscall
+    pub fn set_syscall_ret(&mut self, ret: usize) {
+        self.general.x0 = ret;
+    }
+
+    /// Get syscall args
+    pub fn get_syscall_args(&self) -> [usize; 6] {
+        [
+            self.general.x0,
+            self.general.x1,
+            self.general.x2,
+            self.general.x3,
+            self.general.x4,
+            self.general.x5,
+        ]
+    }
+
+    /// Set instruction pointer
+    pub fn set_ip(&mut self, ip: usize) {
+        self.elr = ip;
+    }
+
+    /// Set stack pointer
+    pub fn set_sp(&mut self, sp: usize) {
+        self.general.sp = sp;
+    }
+
+    /// Get stack pointer
+    pub fn get_sp(&self) -> usize {
+        self.general.sp
+    }
+
+    /// Set tls pointer
+    pub fn set_tls(&mut self, tls: usize) {
+        self.tpidr = tls;
+    }
+}
+
+#[allow(improper_ctypes)]
+extern "C" {
+    fn trap_vector_table();
+    fn run_user(regs: &mut UserContext) -> usize;
+}
@@ -4,11 +4,15 @@
 //!
 //! Each architecture that Asterinas supports may contain a submodule here.
 
+#[cfg(target_arch = "aarch64")]
+pub mod aarch64;
 #[cfg(target_arch = "riscv64")]
 pub mod riscv;
 #[cfg(target_arch = "x86_64")]
 pub mod x86;
 
+#[cfg(target_arch = "aarch64")]
+pub use self::aarch64::*;
 #[cfg(target_arch = "riscv64")]
 pub use self::riscv::*;
 #[cfg(target_arch = "x86_64")]
@@ -4,7 +4,7 @@
 
 pub mod smp;
 
-use core::arch::global_asm;
+use core::{arch::global_asm, sync::atomic::Ordering};
 
 use fdt::Fdt;
 use spin::Once;
@@ -100,9 +100,11 @@ fn parse_initramfs_range() -> Option<(usize, usize)> {
 
 /// The entry point of the Rust code portion of Asterinas.
 #[no_mangle]
-pub extern "C" fn riscv_boot(_hart_id: usize, device_tree_paddr: usize) -> ! {
+pub extern "C" fn riscv_boot(hart_id: usize, device_tree_paddr: usize) -> ! {
     early_println!("Enter riscv_boot");
 
+    smp::BOOT_HART_ID.store(hart_id, Ordering::Relaxed);
+
     let device_tree_ptr = paddr_to_vaddr(device_tree_paddr) as *const u8;
     let fdt = unsafe { fdt::Fdt::from_ptr(device_tree_ptr).unwrap() };
     DEVICE_TREE.call_once(|| fdt);
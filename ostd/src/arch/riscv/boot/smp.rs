@@ -1,11 +1,95 @@
 // SPDX-License-Identifier: MPL-2.0
 
 //! Multiprocessor Boot Support
+//!
+//! The harts (hardware threads) other than the boot hart are started by the SBI
+//! firmware through the Hart State Management (HSM) extension. A started hart
+//! jumps to `_start_ap` in the physical address space, with its hart ID in `a0`
+//! and the opaque argument, which is the CPU ID assigned by the kernel, in `a1`.
+//!
+//! The boot hart may not be hart 0 (e.g., OpenSBI picks the boot hart randomly),
+//! so the CPU IDs are not the hart IDs. The boot hart is always CPU 0, and the
+//! other harts are numbered in the order that they appear in the device tree.
 
+use alloc::vec::Vec;
+use core::sync::atomic::{AtomicUsize, Ordering};
+
+use spin::Once;
+
+use super::DEVICE_TREE;
+use crate::{
+    cpu::CpuId,
+    mm::{kspace::kernel_loaded_offset, paddr_to_vaddr},
+};
+
+/// The hart ID of the boot hart.
+pub(super) static BOOT_HART_ID: AtomicUsize = AtomicUsize::new(0);
+
+/// The hart IDs of the CPUs, indexed by the CPU IDs.
+static HART_IDS: Once<Vec<usize>> = Once::new();
+
+fn hart_ids() -> &'static [usize] {
+    HART_IDS.call_once(|| {
+        let boot_hart_id = BOOT_HART_ID.load(Ordering::Relaxed);
+
+        let mut hart_ids = Vec::new();
+        hart_ids.push(boot_hart_id);
+        for cpu in DEVICE_TREE.get().unwrap().cpus() {
+            let is_disabled = cpu
+                .property("status")
+                .and_then(|status| status.as_str())
+                .is_some_and(|status| status != "okay");
+            let hart_id = cpu.ids().first();
+            if !is_disabled && hart_id != boot_hart_id {
+                hart_ids.push(hart_id);
+            }
+        }
+        hart_ids
+    })
+}
+
+/// Returns the hart ID of a CPU.
+pub(crate) fn hart_id_of(cpu_id: CpuId) -> usize {
+    hart_ids()[cpu_id.as_usize()]
+}
+
+/// Get the number of processors
 pub(crate) fn get_num_processors() -> Option<u32> {
-    Some(1)
+    Some(hart_ids().len() as u32)
 }
 
+/// Brings up all application processors.
 pub(crate) fn bringup_all_aps() {
-    // TODO
+    fill_boot_stack_array_ptr();
+
+    extern "C" {
+        fn _start_ap();
+    }
+    let start_paddr = _start_ap as usize - kernel_loaded_offset();
+
+    for (cpu_id, hart_id) in hart_ids().iter().enumerate().skip(1) {
+        let ret = sbi_rt::hart_start(*hart_id, start_paddr, cpu_id);
+        if ret.error != 0 {
+            log::warn!("Failed to start hart {}: {:?}", hart_id, ret);
+        }
+    }
+}
+
+/// Initializes the boot stack array in the AP boot code with the given pages.
+fn fill_boot_stack_array_ptr() {
+    let pages = &crate::boot::smp::AP_BOOT_INFO
+        .get()
+        .unwrap()
+        .boot_stack_array;
+
+    extern "C" {
+        static __ap_boot_stack_array_pointer: u64;
+    }
+
+    // SAFETY: This pointer points to a static variable defined in the `boot.S`.
+    let ptr = unsafe { &__ap_boot_stack_array_pointer as *const u64 as *mut u64 };
+    // SAFETY: We only write to it once.
+    unsafe {
+        ptr.write_volatile(paddr_to_vaddr(pages.start_paddr()) as u64);
+    }
 }
@@ -9,8 +9,16 @@ use core::fmt::Debug;
 use riscv::register::scause::{Exception, Trap};
 
 pub use super::trap::GeneralRegs as RawGeneralRegs;
-use super::trap::{TrapFrame, UserContext as RawUserContext};
-use crate::user::{ReturnReason, UserContextApi, UserContextApiInternal};
+use super::trap::{handle_interrupt, TrapFrame, UserContext as RawUserContext};
+use crate::{
+    task::scheduler,
+    user::{ReturnReason, UserContextApi, UserContextApiInternal},
+};
+
+/// The previous interrupt-enable bit of `sstatus`.
+const SSTATUS_SPIE: usize = 1 << 5;
+/// The previous privilege bit of `sstatus`.
+const SSTATUS_SPP: usize = 1 << 8;
 
 /// Cpu context, including both general-purpose registers and FPU state.
 #[derive(Clone, Copy, Debug)]
@@ -108,10 +116,19 @@ impl UserContextApiInternal for UserContext {
     where
         F: FnMut() -> bool,
     {
+        // Set `sstatus.SPIE` so that the user space can receive the interrupts, and clear
+        // `sstatus.SPP` so that `sret` returns to the user mode.
+        self.user_context.sstatus |= SSTATUS_SPIE;
+        self.user_context.sstatus &= !SSTATUS_SPP;
+
         let ret = loop {
+            scheduler::might_preempt();
             self.user_context.run();
             match riscv::register::scause::read().cause() {
-                Trap::Interrupt(_) => todo!(),
+                Trap::Interrupt(interrupt) => {
+                    handle_interrupt(interrupt, &self.as_trap_frame());
+                    crate::arch::irq::enable_local();
+                }
                 Trap::Exception(Exception::UserEnvCall) => {
                     self.user_context.sepc += 4;
                     break ReturnReason::UserSyscall;
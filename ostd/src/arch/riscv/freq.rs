@@ -0,0 +1,30 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! The frequencies of RISC-V CPUs.
+//!
+//! The frequencies of the harts are not reported by the SBI, and the device
+//! tree properties that describe them are not parsed yet, so they are unknown.
+
+use crate::{cpu::freq::FreqInfo, trap::DisabledLocalIrqGuard};
+
+pub(crate) fn probe() -> FreqInfo {
+    FreqInfo {
+        min_khz: 0,
+        base_khz: 0,
+        max_khz: 0,
+    }
+}
+
+pub(crate) fn measure_khz(_guard: &DisabledLocalIrqGuard, _info: &FreqInfo) -> Option<u64> {
+    None
+}
+
+pub(crate) fn can_scale() -> bool {
+    false
+}
+
+pub(crate) fn driver_name() -> Option<&'static str> {
+    None
+}
+
+pub(crate) fn set_target_khz(_guard: &DisabledLocalIrqGuard, _target_khz: u64) {}
@@ -3,14 +3,18 @@
 //! Interrupts.
 
 use alloc::{boxed::Box, fmt::Debug, sync::Arc, vec::Vec};
+use core::sync::atomic::{AtomicU64, Ordering};
 
 use id_alloc::IdAlloc;
+use sbi_rt::HartMask;
 use spin::Once;
 
 use crate::{
-    cpu::CpuId,
+    cpu::{CpuId, PinCurrentCpu},
+    cpu_local,
     sync::{Mutex, PreemptDisabled, SpinLock, SpinLockGuard},
-    trap::TrapFrame,
+    trap::{call_irq_callback_functions, TrapFrame},
+    Error, Result,
 };
 
 /// The global allocator for software defined IRQ lines.
@@ -39,6 +43,16 @@ pub(crate) fn disable_local() {
     riscv::interrupt::disable();
 }
 
+/// Halts the CPU until the next interrupt and then enables local IRQs.
+///
+/// A pending interrupt wakes up the CPU even if local IRQs are disabled, so
+/// the interrupt is handled right after the IRQs are enabled.
+pub(crate) fn enable_local_and_halt() {
+    // SAFETY: Waiting for interrupts does not affect memory safety.
+    unsafe { core::arch::asm!("wfi") };
+    enable_local();
+}
+
 pub(crate) fn is_local_enabled() -> bool {
     riscv::register::sstatus::read().sie()
 }
@@ -88,6 +102,25 @@ impl IrqLine {
         self.irq_num
     }
 
+    /// Returns the CPU that the IRQ is delivered to.
+    pub fn affinity(&self) -> CpuId {
+        CpuId::bsp()
+    }
+
+    /// Delivers the IRQ to a CPU.
+    ///
+    /// TODO: Route the external interrupts through the PLIC contexts of other harts.
+    /// For now, they are always delivered to the BSP.
+    pub fn set_affinity(&self, cpu_id: CpuId) -> Result<()> {
+        if cpu_id != CpuId::bsp() {
+            return Err(Error::InvalidArgs);
+        }
+        Ok(())
+    }
+
+    /// Resets the affinity after the IRQ line is freed.
+    pub(crate) fn reset_affinity(&self) {}
+
     pub fn callback_list(
         &self,
     ) -> SpinLockGuard<alloc::vec::Vec<CallbackElement>, PreemptDisabled> {
@@ -140,12 +173,50 @@ impl Drop for IrqCallbackHandle {
     }
 }
 
+cpu_local! {
+    /// The IRQ numbers of the pending IPIs of each CPU, as a bitmap.
+    static PENDING_IPIS: [AtomicU64; 4] = [
+        AtomicU64::new(0),
+        AtomicU64::new(0),
+        AtomicU64::new(0),
+        AtomicU64::new(0),
+    ];
+}
+
 /// Sends a general inter-processor interrupt (IPI) to the specified CPU.
 ///
+/// RISC-V software interrupts do not carry interrupt numbers, so the IRQ number is
+/// recorded in the pending IPIs of the target CPU before the supervisor software
+/// interrupt is sent to its hart with the SBI.
+///
 /// # Safety
 ///
 /// The caller must ensure that the CPU ID and the interrupt number corresponds
 /// to a safe function to call.
 pub(crate) unsafe fn send_ipi(cpu_id: CpuId, irq_num: u8) {
-    unimplemented!()
+    let pending_ipis = PENDING_IPIS.get_on_cpu(cpu_id);
+    pending_ipis[irq_num as usize / 64].fetch_or(1 << (irq_num % 64), Ordering::Release);
+
+    let hart_id = super::boot::smp::hart_id_of(cpu_id);
+    let ret = sbi_rt::send_ipi(HartMask::from_mask_base(1, hart_id));
+    debug_assert_eq!(ret.error, 0);
+}
+
+/// Handles the supervisor software interrupt, which indicates pending IPIs.
+pub(super) fn handle_ipi(trap_frame: &TrapFrame) {
+    // Clear the interrupt before taking the pending IPIs, so that no IPI is missed.
+    //
+    // SAFETY: Clearing `sip.SSIP` acknowledges the software interrupt.
+    unsafe { core::arch::asm!("csrc sip, {}", in(reg) 1 << 1) };
+
+    let irq_guard = crate::trap::disable_local();
+    let pending_ipis = PENDING_IPIS.get_on_cpu(irq_guard.current_cpu());
+    for (i, pending) in pending_ipis.iter().enumerate() {
+        let mut bits = pending.swap(0, Ordering::Acquire);
+        while bits != 0 {
+            let irq_num = i * 64 + bits.trailing_zeros() as usize;
+            bits &= bits - 1;
+            call_irq_callback_functions(trap_frame, irq_num);
+        }
+    }
 }
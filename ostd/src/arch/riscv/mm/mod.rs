@@ -4,6 +4,7 @@ use alloc::fmt;
 use core::ops::Range;
 
 use crate::{
+    cpu::CpuSet,
     mm::{
         page_prop::{CachePolicy, PageFlags, PageProperty, PrivilegedPageFlags as PrivFlags},
         page_table::PageTableEntryTrait,
@@ -83,6 +84,20 @@ pub(crate) fn tlb_flush_all_including_global() {
     riscv::asm::sfence_vma_all()
 }
 
+/// Makes the instruction caches of all CPUs coherent with the data caches.
+///
+/// `fence.i` only synchronizes the instruction cache of the current CPU, so
+/// all the CPUs are requested to execute it with inter-processor calls.
+pub(crate) fn sync_icache_all() {
+    crate::smp::inter_processor_call(&CpuSet::new_full(), sync_icache_local);
+}
+
+fn sync_icache_local() {
+    // SAFETY: `fence.i` only synchronizes the instruction fetches with the
+    // prior stores, which does not affect memory safety.
+    unsafe { core::arch::asm!("fence.i") };
+}
+
 #[derive(Clone, Copy, Pod, Default)]
 #[repr(C)]
 pub struct PageTableEntry(usize);
@@ -5,10 +5,14 @@
 pub mod boot;
 pub(crate) mod cpu;
 pub mod device;
+pub(crate) mod freq;
+pub(crate) mod idle;
 pub mod iommu;
 pub(crate) mod irq;
 pub(crate) mod mm;
 pub(crate) mod pci;
+pub(crate) mod plic;
+pub(crate) mod power;
 pub mod qemu;
 pub mod serial;
 pub mod task;
@@ -17,11 +21,22 @@ pub mod trap;
 
 use core::sync::atomic::Ordering;
 
+use crate::{cpu::PinCurrentCpu, mm::numa::NumaTopology};
+
 #[cfg(feature = "cvm_guest")]
 pub(crate) fn init_cvm_guest() {
     // Unimplemented, no-op
 }
 
+pub(crate) fn init_firmware_tables() {
+    // Unimplemented, no-op
+}
+
+pub(crate) fn numa_topology() -> Option<NumaTopology> {
+    // Unimplemented, no NUMA topology
+    None
+}
+
 pub(crate) fn init_on_bsp() {
     // SAFETY: this function is only called once on BSP.
     unsafe {
@@ -39,17 +54,37 @@ pub(crate) fn init_on_bsp() {
     // we are on the BSP.
     unsafe { crate::cpu::local::init_on_bsp() };
 
+    plic::init();
+    enable_software_interrupt();
+
     crate::boot::smp::boot_all_aps();
 
     timer::init();
 }
 
+/// Architecture-specific initialization on the application processor.
+///
+/// # Safety
+///
+/// This function must be called only once on each application processor.
+/// And it should be called after the BSP's call to [`init_on_bsp`].
 pub(crate) unsafe fn init_on_ap() {
-    unimplemented!()
+    let cpu_id = crate::trap::disable_local().current_cpu();
+    plic::init_on_ap(cpu_id);
+    enable_software_interrupt();
+}
+
+/// Completes the external interrupt claimed from the PLIC, if any.
+///
+/// The timer interrupts and the software interrupts (i.e., IPIs) are acknowledged
+/// before their handlers are called.
+pub(crate) fn interrupts_ack(_irq_number: usize) {
+    plic::complete();
 }
 
-pub(crate) fn interrupts_ack(irq_number: usize) {
-    unimplemented!()
+fn enable_software_interrupt() {
+    // SAFETY: The supervisor software interrupts (i.e., IPIs) are handled by the trap handler.
+    unsafe { riscv::register::sie::set_ssoft() };
 }
 
 /// Return the frequency of TSC. The unit is Hz.
@@ -0,0 +1,270 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! The Platform-Level Interrupt Controller (PLIC).
+//!
+//! The PLIC routes the external interrupts of the devices (the interrupt sources)
+//! to the contexts of the harts. Each hart has one context for each privilege mode,
+//! and the kernel uses the supervisor contexts. An interrupt is claimed by a context
+//! before it is handled, and it must be completed afterwards so that the source can
+//! interrupt again.
+//!
+//! Ref: <https://github.com/riscv/riscv-plic-spec/blob/master/riscv-plic.adoc>
+
+use alloc::vec::Vec;
+use core::sync::atomic::{AtomicU32, Ordering};
+
+use log::info;
+use spin::Once;
+
+use super::boot::{smp::hart_id_of, DEVICE_TREE};
+use crate::{
+    cpu::CpuId,
+    cpu_local_cell,
+    mm::{paddr_to_vaddr, Vaddr},
+    sync::SpinLock,
+    trap::IrqLine,
+    Error, Result,
+};
+
+const PRIORITY_BASE: usize = 0x0;
+const ENABLE_BASE: usize = 0x2000;
+const ENABLE_STRIDE: usize = 0x80;
+const CONTEXT_BASE: usize = 0x20_0000;
+const CONTEXT_STRIDE: usize = 0x1000;
+const THRESHOLD_OFFSET: usize = 0x0;
+const CLAIM_COMPLETE_OFFSET: usize = 0x4;
+
+/// The local interrupt number of the supervisor external interrupts.
+const SUPERVISOR_EXTERNAL_INTERRUPT: u32 = 9;
+
+/// The value in [`Plic::irq_nums`] for the sources without IRQ lines.
+const NO_IRQ: u32 = u32::MAX;
+
+pub(crate) static PLIC: Once<Plic> = Once::new();
+
+cpu_local_cell! {
+    /// The supervisor context of the current CPU.
+    static CONTEXT: u32 = 0;
+    /// The interrupt source that is claimed but not completed on the current CPU,
+    /// or zero if there is none.
+    static CLAIMED_SOURCE: u32 = 0;
+}
+
+/// The Platform-Level Interrupt Controller.
+pub(crate) struct Plic {
+    /// The virtual address of the MMIO registers in the linear mapping.
+    ///
+    /// The registers are accessed before the kernel page table is activated, so they
+    /// cannot be mapped as an `IoMem`.
+    base: Vaddr,
+    /// The number of the interrupt sources. The source 0 does not exist.
+    num_sources: u32,
+    /// The supervisor context of each CPU, indexed by the CPU IDs.
+    contexts: Vec<u32>,
+    /// The IRQ number of each interrupt source.
+    irq_nums: Vec<AtomicU32>,
+    /// The IRQ lines of the enabled interrupt sources.
+    irqs: SpinLock<Vec<(u32, IrqLine)>>,
+}
+
+impl Plic {
+    /// Enables an interrupt source, delivering its interrupts to the IRQ line.
+    ///
+    /// TODO: Deliver the interrupts to the CPUs other than the BSP.
+    pub fn enable(&self, source: u32, irq: IrqLine) -> Result<()> {
+        if source == 0 || source > self.num_sources {
+            return Err(Error::InvalidArgs);
+        }
+
+        let mut irqs = self.irqs.lock();
+        if irqs
+            .iter()
+            .any(|(enabled_source, _)| *enabled_source == source)
+        {
+            return Err(Error::AccessDenied);
+        }
+
+        self.irq_nums[source as usize].store(irq.num() as u32, Ordering::Relaxed);
+        self.write_priority(source, 1);
+        self.set_enabled(self.contexts[CpuId::bsp().as_usize()], source, true);
+        irqs.push((source, irq));
+        Ok(())
+    }
+
+    /// Disables an interrupt source.
+    pub fn disable(&self, source: u32) -> Result<()> {
+        if source == 0 || source > self.num_sources {
+            return Err(Error::InvalidArgs);
+        }
+
+        let mut irqs = self.irqs.lock();
+        self.set_enabled(self.contexts[CpuId::bsp().as_usize()], source, false);
+        self.write_priority(source, 0);
+        self.irq_nums[source as usize].store(NO_IRQ, Ordering::Relaxed);
+        irqs.retain(|(enabled_source, _)| *enabled_source != source);
+        Ok(())
+    }
+
+    /// Reads a register from the MMIO region.
+    fn read(&self, offset: usize) -> u32 {
+        debug_assert!(offset % 4 == 0);
+        // SAFETY: The offset is a register of the PLIC, which is mapped in the linear mapping.
+        unsafe { core::ptr::read_volatile((self.base + offset) as *const u32) }
+    }
+
+    /// Writes a register in the MMIO region.
+    fn write(&self, offset: usize, val: u32) {
+        debug_assert!(offset % 4 == 0);
+        // SAFETY: The offset is a register of the PLIC, which is mapped in the linear mapping.
+        unsafe { core::ptr::write_volatile((self.base + offset) as *mut u32, val) }
+    }
+
+    fn write_priority(&self, source: u32, priority: u32) {
+        self.write(PRIORITY_BASE + 4 * source as usize, priority);
+    }
+
+    fn set_enabled(&self, context: u32, source: u32, is_enabled: bool) {
+        let offset = ENABLE_BASE + ENABLE_STRIDE * context as usize + 4 * (source as usize / 32);
+        let mut bits = self.read(offset);
+        if is_enabled {
+            bits |= 1 << (source % 32);
+        } else {
+            bits &= !(1 << (source % 32));
+        }
+        self.write(offset, bits);
+    }
+
+    fn context_offset(context: u32) -> usize {
+        CONTEXT_BASE + CONTEXT_STRIDE * context as usize
+    }
+}
+
+/// Initializes the PLIC and the supervisor context of the BSP.
+pub(super) fn init() {
+    let device_tree = DEVICE_TREE.get().unwrap();
+    let Some(node) = device_tree.find_compatible(&["riscv,plic0", "sifive,plic-1.0.0"]) else {
+        log::warn!("[PLIC]: No PLIC found. External interrupts are disabled.");
+        return;
+    };
+
+    let base = node.reg().unwrap().next().unwrap().starting_address as usize;
+    let num_sources = node
+        .property("riscv,ndev")
+        .and_then(|ndev| ndev.as_usize())
+        .unwrap() as u32;
+
+    // The `interrupts-extended` property lists the contexts in order, each of which is the
+    // phandle of the interrupt controller of a hart and the local interrupt number.
+    let contexts_extended: Vec<(u32, u32)> = node
+        .property("interrupts-extended")
+        .unwrap()
+        .value
+        .chunks_exact(8)
+        .map(|cells| {
+            let phandle = u32::from_be_bytes(cells[0..4].try_into().unwrap());
+            let interrupt = u32::from_be_bytes(cells[4..8].try_into().unwrap());
+            (phandle, interrupt)
+        })
+        .collect();
+    let contexts = crate::cpu::all_cpus()
+        .map(|cpu_id| {
+            let phandle = hart_interrupt_controller(hart_id_of(cpu_id));
+            contexts_extended
+                .iter()
+                .position(|context| *context == (phandle, SUPERVISOR_EXTERNAL_INTERRUPT))
+                .unwrap() as u32
+        })
+        .collect();
+
+    let irq_nums = (0..=num_sources).map(|_| AtomicU32::new(NO_IRQ)).collect();
+
+    info!(
+        "[PLIC]: Found PLIC at {:#x} with {} interrupt sources",
+        base, num_sources
+    );
+    PLIC.call_once(|| Plic {
+        base: paddr_to_vaddr(base),
+        num_sources,
+        contexts,
+        irq_nums,
+        irqs: SpinLock::new(Vec::new()),
+    });
+
+    init_current_context(CpuId::bsp());
+}
+
+/// Initializes the supervisor context of the current AP.
+pub(super) fn init_on_ap(cpu_id: CpuId) {
+    init_current_context(cpu_id);
+}
+
+fn init_current_context(cpu_id: CpuId) {
+    let Some(plic) = PLIC.get() else {
+        return;
+    };
+
+    let context = plic.contexts[cpu_id.as_usize()];
+    CONTEXT.store(context);
+    // Accept the interrupts of all priorities.
+    plic.write(Plic::context_offset(context) + THRESHOLD_OFFSET, 0);
+
+    // SAFETY: The supervisor external interrupts are handled by the trap handler.
+    unsafe { riscv::register::sie::set_sext() };
+}
+
+/// Claims a pending interrupt on the current CPU, returning its IRQ number.
+///
+/// The interrupt is completed by [`complete`] after it is handled.
+pub(super) fn claim() -> Option<u8> {
+    let plic = PLIC.get()?;
+    let offset = Plic::context_offset(CONTEXT.load()) + CLAIM_COMPLETE_OFFSET;
+
+    loop {
+        let source = plic.read(offset);
+        if source == 0 {
+            return None;
+        }
+
+        let irq_num = plic
+            .irq_nums
+            .get(source as usize)
+            .map_or(NO_IRQ, |irq_num| irq_num.load(Ordering::Relaxed));
+        if irq_num == NO_IRQ {
+            // The source is disabled after it interrupts. Ignore it.
+            plic.write(offset, source);
+            continue;
+        }
+
+        CLAIMED_SOURCE.store(source);
+        return Some(irq_num as u8);
+    }
+}
+
+/// Completes the interrupt claimed on the current CPU, if any.
+pub(super) fn complete() {
+    let source = CLAIMED_SOURCE.load();
+    if source == 0 {
+        return;
+    }
+    CLAIMED_SOURCE.store(0);
+
+    let plic = PLIC.get().unwrap();
+    let offset = Plic::context_offset(CONTEXT.load()) + CLAIM_COMPLETE_OFFSET;
+    plic.write(offset, source);
+}
+
+/// Returns the phandle of the interrupt controller of a hart.
+fn hart_interrupt_controller(hart_id: usize) -> u32 {
+    let cpus = DEVICE_TREE.get().unwrap().find_node("/cpus").unwrap();
+    cpus.children()
+        .filter(|cpu| {
+            cpu.reg()
+                .and_then(|mut reg| reg.next())
+                .is_some_and(|reg| reg.starting_address as usize == hart_id)
+        })
+        .flat_map(|cpu| cpu.children())
+        .find(|child| child.property("interrupt-controller").is_some())
+        .and_then(|controller| controller.property("phandle"))
+        .and_then(|phandle| phandle.as_usize())
+        .unwrap() as u32
+}
@@ -0,0 +1,30 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! Turning off and restarting the RISC-V machine with the SBI.
+
+pub(crate) fn poweroff() -> ! {
+    super::irq::disable_local();
+
+    sbi_rt::system_reset(sbi_rt::Shutdown, sbi_rt::NoReason);
+
+    log::warn!("Failed to turn off the machine, halting");
+    halt()
+}
+
+pub(crate) fn restart() -> ! {
+    super::irq::disable_local();
+
+    sbi_rt::system_reset(sbi_rt::ColdReboot, sbi_rt::NoReason);
+
+    log::warn!("Failed to restart the machine, halting");
+    halt()
+}
+
+pub(crate) fn halt() -> ! {
+    super::irq::disable_local();
+
+    loop {
+        // SAFETY: Waiting for interrupts does not affect memory safety.
+        unsafe { core::arch::asm!("wfi") };
+    }
+}
@@ -11,7 +11,6 @@ core::arch::global_asm!(include_str!("switch.S"));
 pub(crate) struct TaskContext {
     pub regs: CalleeRegs,
     pub pc: usize,
-    pub fsbase: usize,
 }
 
 /// Callee-saved registers.
@@ -72,18 +71,12 @@ impl TaskContext {
         TaskContext {
             regs: CalleeRegs::new(),
             pc: 0,
-            fsbase: 0,
         }
     }
 
-    /// Sets thread-local storage pointer.
-    pub fn set_tls_pointer(&mut self, tls: usize) {
-        self.fsbase = tls;
-    }
-
-    /// Gets thread-local storage pointer.
-    pub fn tls_pointer(&self) -> usize {
-        self.fsbase
+    /// Gets the frame pointer saved when the task is switched out.
+    pub(crate) fn frame_pointer(&self) -> usize {
+        self.regs.s0 as usize
     }
 }
 
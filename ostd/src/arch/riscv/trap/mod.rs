@@ -4,9 +4,15 @@
 
 mod trap;
 
+use core::{mem::size_of, ops::Range};
+
+use riscv::register::scause::Interrupt;
 pub use trap::{GeneralRegs, TrapFrame, UserContext};
 
-use crate::cpu_local_cell;
+use super::{irq, plic, timer};
+use crate::{
+    cpu_local_cell, mm::Vaddr, task::current_kernel_stack, trap::call_irq_callback_functions,
+};
 
 cpu_local_cell! {
     static IS_KERNEL_INTERRUPTED: bool = false;
@@ -24,15 +30,81 @@ pub fn is_kernel_interrupted() -> bool {
     IS_KERNEL_INTERRUPTED.load()
 }
 
+/// Walks the call chain of the kernel code that is interrupted with the trap frame.
+///
+/// The visitor is called with the interrupted instruction pointer first, and then
+/// with the return address of each caller, until it returns `false` or the chain
+/// ends. The callers are found by following the frame pointers, so the call chain
+/// is complete only if the kernel is built with frame pointers. The frame pointers
+/// are followed only within the kernel stack of the current task.
+pub fn walk_kernel_stack(f: &TrapFrame, mut visit: impl FnMut(Vaddr) -> bool) {
+    if !visit(f.sepc) {
+        return;
+    }
+    let Some(stack) = current_kernel_stack() else {
+        return;
+    };
+    walk_frame_pointers(f.general.s0, stack, visit);
+}
+
+/// Walks the call chain by following the frame pointers from `frame_pointer`.
+///
+/// The visitor is called with the return address of each caller, until it returns
+/// `false` or the chain ends. Only the frames within `stack` are followed.
+pub(crate) fn walk_frame_pointers(
+    mut frame_pointer: usize,
+    stack: Range<Vaddr>,
+    mut visit: impl FnMut(Vaddr) -> bool,
+) {
+    // The return address and the caller's frame pointer are saved right below the
+    // address that each frame pointer points to.
+    while frame_pointer % size_of::<usize>() == 0
+        && stack.start + 2 * size_of::<usize>() <= frame_pointer
+        && frame_pointer <= stack.end
+    {
+        // SAFETY: The two words are within the kernel stack, which is mapped. The stack
+        // may be modified concurrently if it is not the interrupted one, so the words
+        // are read as volatile and are only used as hints.
+        let (caller_frame_pointer, return_address) = unsafe {
+            let frame = (frame_pointer as *const usize).sub(2);
+            (frame.read_volatile(), frame.add(1).read_volatile())
+        };
+        if return_address == 0 || !visit(return_address) {
+            return;
+        }
+        // The stack grows downwards, so the frames of the callers are at higher addresses.
+        if caller_frame_pointer <= frame_pointer {
+            return;
+        }
+        frame_pointer = caller_frame_pointer;
+    }
+}
+
+/// Handles an interrupt from either the kernel or the user space.
+pub(super) fn handle_interrupt(interrupt: Interrupt, trap_frame: &TrapFrame) {
+    match interrupt {
+        Interrupt::SupervisorTimer => {
+            call_irq_callback_functions(trap_frame, timer::timer_irq_num() as usize);
+        }
+        Interrupt::SupervisorSoft => irq::handle_ipi(trap_frame),
+        Interrupt::SupervisorExternal => {
+            while let Some(irq_num) = plic::claim() {
+                call_irq_callback_functions(trap_frame, irq_num as usize);
+            }
+        }
+        interrupt => log::warn!("Unexpected interrupt: {:?}", interrupt),
+    }
+}
+
 /// Handle traps (only from kernel).
 #[no_mangle]
 extern "C" fn trap_handler(f: &mut TrapFrame) {
     use riscv::register::scause::Trap;
 
     match riscv::register::scause::read().cause() {
-        Trap::Interrupt(_) => {
+        Trap::Interrupt(interrupt) => {
             IS_KERNEL_INTERRUPTED.store(true);
-            todo!();
+            handle_interrupt(interrupt, f);
             IS_KERNEL_INTERRUPTED.store(false);
         }
         Trap::Exception(e) => {
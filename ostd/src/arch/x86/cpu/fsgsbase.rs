@@ -0,0 +1,167 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! The base addresses of the FS and GS segments.
+//!
+//! The kernel uses the GS segment for the CPU-local storage, while the user space uses the FS
+//! and GS segments for its thread-local storage. When the kernel is running, the GS base of the
+//! user space is swapped into the `IA32_KERNEL_GS_BASE` model-specific register by `swapgs`.
+//!
+//! The `FSGSBASE` instructions are used to access the base addresses if they are supported,
+//! since they are much faster than the model-specific registers. They are also available to the
+//! user space in that case, so the base addresses can be changed without the kernel's knowledge.
+//!
+//! In the 32-bit compatibility mode, the user space loads the GS base from a segment descriptor
+//! by loading the GS selector. The kernel never loads the GS selector for itself, so the selector
+//! is kept untouched while the kernel is running.
+
+use core::sync::atomic::{AtomicBool, Ordering};
+
+use x86::{
+    bits64::segmentation::{rdfsbase, rdgsbase, wrfsbase, wrgsbase},
+    msr::{rdmsr, wrmsr, IA32_FS_BASE, IA32_GS_BASE, IA32_KERNEL_GSBASE},
+};
+use x86_64::registers::control::{Cr4, Cr4Flags};
+
+use super::RawUserContext;
+use crate::cpu_local_cell;
+
+static HAS_FSGSBASE: AtomicBool = AtomicBool::new(false);
+
+cpu_local_cell! {
+    /// The FS base of the user space that is loaded on this CPU.
+    static LOADED_USER_FS_BASE: usize = 0;
+    /// The GS base of the user space that is loaded on this CPU.
+    static LOADED_USER_GS_BASE: usize = 0;
+}
+
+/// Enables the `FSGSBASE` instructions on the current CPU if they are supported.
+///
+/// This function must be called on each CPU before the CPU-local storage is accessed.
+pub(in crate::arch) fn init() {
+    let cpuid = x86::cpuid::CpuId::new();
+    let has_fsgsbase = cpuid
+        .get_extended_feature_info()
+        .is_some_and(|info| info.has_fsgsbase());
+    if !has_fsgsbase {
+        return;
+    }
+
+    // SAFETY: Enabling the supported `FSGSBASE` instructions does not affect memory safety.
+    unsafe { Cr4::update(|cr4| cr4.insert(Cr4Flags::FSGSBASE)) };
+    HAS_FSGSBASE.store(true, Ordering::Relaxed);
+}
+
+/// Returns whether the `FSGSBASE` instructions are enabled, including for the user space.
+pub fn has_fsgsbase() -> bool {
+    HAS_FSGSBASE.load(Ordering::Relaxed)
+}
+
+/// Reads the GS base of the kernel.
+pub(crate) fn read_kernel_gs_base() -> u64 {
+    if has_fsgsbase() {
+        // SAFETY: The `FSGSBASE` instructions are enabled.
+        unsafe { rdgsbase() }
+    } else {
+        // SAFETY: Reading the GS base has no side effects.
+        unsafe { rdmsr(IA32_GS_BASE) }
+    }
+}
+
+/// Writes the GS base of the kernel.
+///
+/// # Safety
+///
+/// The caller must ensure that the new base address points to the CPU-local storage of the
+/// current CPU.
+pub(crate) unsafe fn write_kernel_gs_base(base: u64) {
+    if has_fsgsbase() {
+        wrgsbase(base);
+    } else {
+        wrmsr(IA32_GS_BASE, base);
+    }
+}
+
+/// Loads the FS and GS bases of the user context on the current CPU.
+///
+/// The model-specific registers are written only if the bases are changed, since writing them
+/// is expensive.
+pub(super) fn load_user_bases(user_context: &RawUserContext) {
+    let fs_base = user_context.general.fsbase;
+    if fs_base != LOADED_USER_FS_BASE.load() {
+        // SAFETY: The kernel does not use the FS segment.
+        unsafe {
+            if has_fsgsbase() {
+                wrfsbase(fs_base as u64);
+            } else {
+                wrmsr(IA32_FS_BASE, fs_base as u64);
+            }
+        }
+        LOADED_USER_FS_BASE.store(fs_base);
+    }
+
+    let gs_base = user_context.general.gsbase;
+    if gs_base != LOADED_USER_GS_BASE.load() {
+        // SAFETY: The GS base of the user space is swapped in when returning to the user space.
+        // It does not affect the CPU-local storage of the kernel.
+        unsafe { wrmsr(IA32_KERNEL_GSBASE, gs_base as u64) };
+        LOADED_USER_GS_BASE.store(gs_base);
+    }
+}
+
+/// Loads the GS selector of the user space on the current CPU.
+///
+/// The GS base of the user space is set to `base` afterwards, regardless of the base that is
+/// loaded from the segment descriptor.
+///
+/// # Safety
+///
+/// The caller must ensure that the local IRQs are disabled and that the selector is the null
+/// selector or refers to a present user data segment.
+pub(super) unsafe fn load_user_gs_selector(selector: u16, base: usize) {
+    // Loading the selector overwrites the GS base, so the GS base of the kernel is swapped out
+    // meanwhile. No CPU-local storage is accessed between the two `swapgs`.
+    core::arch::asm!(
+        "swapgs",
+        "mov gs, {selector:x}",
+        "swapgs",
+        selector = in(reg) selector,
+        options(nostack, preserves_flags),
+    );
+
+    wrmsr(IA32_KERNEL_GSBASE, base as u64);
+    LOADED_USER_GS_BASE.store(base);
+}
+
+/// Reads the GS selector of the user space on the current CPU.
+pub(super) fn read_user_gs_selector() -> u16 {
+    let selector: u16;
+    // SAFETY: Reading the GS selector has no side effects.
+    unsafe {
+        core::arch::asm!(
+            "mov {selector:x}, gs",
+            selector = out(reg) selector,
+            options(nomem, nostack, preserves_flags),
+        )
+    };
+    selector
+}
+
+/// Saves the FS and GS bases of the user space to the user context.
+///
+/// The bases can only be changed by the user space itself if the `FSGSBASE` instructions are
+/// enabled. Otherwise, they are still the ones that were loaded.
+pub(super) fn save_user_bases(user_context: &mut RawUserContext) {
+    if !has_fsgsbase() {
+        return;
+    }
+
+    // SAFETY: The `FSGSBASE` instructions are enabled.
+    let fs_base = unsafe { rdfsbase() } as usize;
+    // SAFETY: Reading the GS base of the user space has no side effects.
+    let gs_base = unsafe { rdmsr(IA32_KERNEL_GSBASE) } as usize;
+
+    user_context.general.fsbase = fs_base;
+    user_context.general.gsbase = gs_base;
+    LOADED_USER_FS_BASE.store(fs_base);
+    LOADED_USER_GS_BASE.store(gs_base);
+}
@@ -2,9 +2,9 @@
 
 //! Architecture dependent CPU-local information utilities.
 
-use x86_64::registers::segmentation::{Segment64, GS};
+use super::fsgsbase::{read_kernel_gs_base, write_kernel_gs_base};
 
-/// Sets the base address for the CPU local storage by writing to the GS base model-specific register.
+/// Sets the base address for the CPU local storage by writing to the GS base.
 /// This operation is marked as `unsafe` because it directly interfaces with low-level CPU registers.
 ///
 /// # Safety
@@ -15,12 +15,12 @@ use x86_64::registers::segmentation::{Segment64, GS};
 ///  - This function should only be called in contexts where the CPU is in a state to accept such changes,
 ///    such as during processor initialization.
 pub(crate) unsafe fn set_base(addr: u64) {
-    GS::write_base(x86_64::addr::VirtAddr::new(addr));
+    write_kernel_gs_base(addr);
 }
 
-/// Gets the base address for the CPU local storage by reading the GS base model-specific register.
+/// Gets the base address for the CPU local storage by reading the GS base.
 pub(crate) fn get_base() -> u64 {
-    GS::read_base().as_u64()
+    read_kernel_gs_base()
 }
 
 use crate::cpu::local::single_instr::{
@@ -2,7 +2,9 @@
 
 //! CPU.
 
+pub(crate) mod fsgsbase;
 pub mod local;
+mod tls;
 
 use alloc::boxed::Box;
 use core::{
@@ -17,7 +19,6 @@ use log::debug;
 use num_derive::FromPrimitive;
 use num_traits::FromPrimitive;
 use spin::Once;
-use x86::bits64::segmentation::wrfsbase;
 pub use x86::cpuid;
 use x86_64::registers::{
     control::{Cr0, Cr0Flags},
@@ -25,9 +26,13 @@ use x86_64::registers::{
     xcontrol::XCr0,
 };
 
+pub use self::tls::{TlsSegment, TlsSegmentFlags, GDT_ENTRY_TLS_MIN, NR_TLS_SEGMENTS};
 pub use super::trap::GeneralRegs as RawGeneralRegs;
 use super::{
-    trap::{TrapFrame, UserContext as RawUserContext},
+    trap::{
+        load_tls_descriptors, load_user_data_selectors, TrapFrame, UserContext as RawUserContext,
+        COMPAT_SYSCALL_VECTOR,
+    },
     CPU_FEATURES,
 };
 use crate::{
@@ -51,6 +56,10 @@ pub struct UserContext {
     user_context: RawUserContext,
     fpu_state: FpuState,
     cpu_exception_info: CpuExceptionInfo,
+    /// The thread-local storage segments of the 32-bit compatibility mode.
+    tls_segments: [Option<TlsSegment>; NR_TLS_SEGMENTS],
+    /// The GS selector of the 32-bit compatibility mode.
+    gs_selector: u16,
 }
 
 /// CPU exception information.
@@ -101,15 +110,84 @@ impl UserContext {
         self.fsbase()
     }
 
-    /// Activates thread-local storage pointer on the current CPU.
+    /// Returns whether the user space runs in the 32-bit compatibility mode.
+    pub fn is_compat_mode(&self) -> bool {
+        self.user_context.compat_mode != 0
+    }
+
+    /// Sets whether the user space runs in the 32-bit compatibility mode.
+    ///
+    /// In the compatibility mode, the user code is executed as 32-bit code and only the low
+    /// halves of the registers are visible to it.
+    pub fn set_compat_mode(&mut self, is_compat: bool) {
+        self.user_context.compat_mode = is_compat as usize;
+    }
+
+    /// Returns whether the system call is issued with `int 0x80`.
+    ///
+    /// Like Linux, such system calls follow the 32-bit ABI even if they are issued in the
+    /// 64-bit mode.
+    pub fn is_compat_syscall(&self) -> bool {
+        self.user_context.trap_num == COMPAT_SYSCALL_VECTOR as usize
+    }
+
+    /// Returns the thread-local storage segment at `index`.
+    ///
+    /// The segment is the GDT entry at `GDT_ENTRY_TLS_MIN + index` when the user space runs in
+    /// the 32-bit compatibility mode.
     ///
-    /// # Safety
+    /// # Panics
     ///
-    /// The method by itself is safe because the value of the TLS register won't affect kernel code.
-    /// But if the user relies on the TLS pointer, make sure that the pointer is correctly set when
-    /// entering the user space.
-    pub fn activate_tls_pointer(&self) {
-        unsafe { wrfsbase(self.fsbase() as u64) }
+    /// This method panics if `index` is not less than [`NR_TLS_SEGMENTS`].
+    pub fn tls_segment(&self, index: usize) -> Option<TlsSegment> {
+        self.tls_segments[index]
+    }
+
+    /// Sets the thread-local storage segment at `index`.
+    ///
+    /// # Panics
+    ///
+    /// This method panics if `index` is not less than [`NR_TLS_SEGMENTS`].
+    pub fn set_tls_segment(&mut self, index: usize, segment: Option<TlsSegment>) {
+        self.tls_segments[index] = segment;
+    }
+
+    /// Returns the GS selector of the 32-bit compatibility mode.
+    pub fn gs_selector(&self) -> u16 {
+        self.gs_selector
+    }
+
+    /// Sets the GS selector of the 32-bit compatibility mode.
+    ///
+    /// If the selector refers to neither a present thread-local storage segment nor the CPUNODE
+    /// segment, the null selector is loaded when returning to the user space.
+    pub fn set_gs_selector(&mut self, selector: u16) {
+        self.gs_selector = selector;
+    }
+
+    /// Clears the thread-local storage segments and the GS selector.
+    pub fn clear_tls_segments(&mut self) {
+        self.tls_segments = [None; NR_TLS_SEGMENTS];
+        self.gs_selector = 0;
+    }
+
+    /// Loads the segments of the 32-bit compatibility mode on the current CPU.
+    ///
+    /// The local IRQs must be disabled.
+    fn load_compat_segments(&mut self) {
+        load_user_data_selectors();
+        load_tls_descriptors(&tls::as_descriptors(&self.tls_segments));
+
+        let base = match tls::check_gs_selector(self.gs_selector, &self.tls_segments) {
+            Some(base) => base,
+            None => {
+                self.gs_selector = 0;
+                0
+            }
+        };
+        self.user_context.general.gsbase = base as usize;
+        // SAFETY: The local IRQs are disabled, and the selector is checked above.
+        unsafe { fsgsbase::load_user_gs_selector(self.gs_selector, base as usize) };
     }
 }
 
@@ -127,7 +205,18 @@ impl UserContextApiInternal for UserContext {
         // return when it is syscall or cpu exception type is Fault or Trap.
         let return_reason = loop {
             scheduler::might_preempt();
+            // The FS and GS bases must not be changed by other tasks before returning to the user
+            // space. `syscall_return` disables the local IRQs by itself, but it is too late.
+            crate::arch::irq::disable_local();
+            if self.is_compat_mode() {
+                self.load_compat_segments();
+            }
+            fsgsbase::load_user_bases(&self.user_context);
             self.user_context.run();
+            fsgsbase::save_user_bases(&mut self.user_context);
+            if self.is_compat_mode() {
+                self.gs_selector = fsgsbase::read_user_gs_selector();
+            }
 
             match CpuException::to_cpu_exception(self.user_context.trap_num as u16) {
                 #[cfg(feature = "cvm_guest")]
@@ -146,7 +235,9 @@ impl UserContextApiInternal for UserContext {
                         self.as_trap_frame()
                     );
                 }
-                None if self.user_context.trap_num == SYSCALL_TRAPNUM => {
+                None if self.user_context.trap_num == SYSCALL_TRAPNUM
+                    || self.user_context.trap_num == COMPAT_SYSCALL_VECTOR as usize =>
+                {
                     crate::arch::irq::enable_local();
                     break ReturnReason::UserSyscall;
                 }
@@ -0,0 +1,131 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! The thread-local storage segments of the 32-bit compatibility mode.
+//!
+//! The 32-bit programs access their thread-local storage through the GS segment, whose base
+//! address comes from a segment descriptor in the GDT. Like Linux, each thread has a few such
+//! descriptors, which are installed to the GDT of the current CPU before the thread returns to
+//! the user space.
+
+use bitflags::bitflags;
+
+use crate::arch::trap::GDT_ENTRY_CPUNODE;
+
+/// The index of the first thread-local storage segment in the GDT, which is the same as Linux.
+pub const GDT_ENTRY_TLS_MIN: usize = 12;
+
+/// The number of the thread-local storage segments of a thread.
+pub const NR_TLS_SEGMENTS: usize = 3;
+
+/// A thread-local storage segment.
+///
+/// Only the user data segments can be described, so the segments cannot be used to access
+/// the kernel.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct TlsSegment {
+    /// The base address of the segment.
+    pub base: u32,
+    /// The limit of the segment, which has 20 bits.
+    pub limit: u32,
+    /// The flags of the segment.
+    pub flags: TlsSegmentFlags,
+}
+
+bitflags! {
+    /// The flags of a [`TlsSegment`].
+    #[derive(Default)]
+    pub struct TlsSegmentFlags: u8 {
+        /// The segment is present.
+        const PRESENT      = 1 << 0;
+        /// The segment is writable.
+        const WRITABLE     = 1 << 1;
+        /// The segment expands down.
+        const EXPAND_DOWN  = 1 << 2;
+        /// The segment is a 32-bit segment.
+        const DEFAULT_SIZE = 1 << 3;
+        /// The limit of the segment is counted in pages.
+        const GRANULARITY  = 1 << 4;
+        /// The bit that is available to the software.
+        const AVAILABLE    = 1 << 5;
+    }
+}
+
+impl TlsSegment {
+    /// Encodes the segment as a segment descriptor of the GDT.
+    pub(super) fn as_descriptor(&self) -> u64 {
+        let base = self.base as u64;
+        let limit = (self.limit & 0xf_ffff) as u64;
+
+        // A data segment with DPL 3. The accessed bit is set, so that the CPU never writes
+        // the descriptor.
+        let mut access = 0x71;
+        if self.flags.contains(TlsSegmentFlags::PRESENT) {
+            access |= 0x80;
+        }
+        if self.flags.contains(TlsSegmentFlags::WRITABLE) {
+            access |= 0x02;
+        }
+        if self.flags.contains(TlsSegmentFlags::EXPAND_DOWN) {
+            access |= 0x04;
+        }
+
+        let mut flags = 0;
+        if self.flags.contains(TlsSegmentFlags::AVAILABLE) {
+            flags |= 0x1;
+        }
+        if self.flags.contains(TlsSegmentFlags::DEFAULT_SIZE) {
+            flags |= 0x4;
+        }
+        if self.flags.contains(TlsSegmentFlags::GRANULARITY) {
+            flags |= 0x8;
+        }
+
+        (limit & 0xffff)
+            | (base & 0xff_ffff) << 16
+            | access << 40
+            | (limit >> 16) << 48
+            | flags << 52
+            | (base >> 24) << 56
+    }
+}
+
+/// Encodes the thread-local storage segments as the segment descriptors of the GDT.
+///
+/// The missing segments are encoded as null descriptors.
+pub(super) fn as_descriptors(
+    segments: &[Option<TlsSegment>; NR_TLS_SEGMENTS],
+) -> [u64; NR_TLS_SEGMENTS] {
+    segments.map(|segment| segment.map_or(0, |segment| segment.as_descriptor()))
+}
+
+/// Checks the GS selector of the user space and returns the base address of the segment.
+///
+/// The selector is set by the kernel on behalf of the user space (e.g., in `sigreturn`), so it
+/// must be checked before it is loaded. Only the null selector, the selectors of the present
+/// thread-local storage segments, and the selector of the CPUNODE segment are accepted.
+pub(super) fn check_gs_selector(
+    selector: u16,
+    segments: &[Option<TlsSegment>; NR_TLS_SEGMENTS],
+) -> Option<u32> {
+    if selector == 0 {
+        return Some(0);
+    }
+
+    // The selector must refer to the GDT with RPL 3.
+    if selector & 0b111 != 0b011 {
+        return None;
+    }
+
+    let index = (selector >> 3) as usize;
+    if index == GDT_ENTRY_CPUNODE {
+        return Some(0);
+    }
+
+    let segment = segments
+        .get(index.checked_sub(GDT_ENTRY_TLS_MIN)?)?
+        .as_ref()?;
+    segment
+        .flags
+        .contains(TlsSegmentFlags::PRESENT)
+        .then_some(segment.base)
+}
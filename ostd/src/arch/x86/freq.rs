@@ -0,0 +1,147 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! The frequencies of x86 CPUs.
+//!
+//! The base and maximum frequencies are reported by the CPUID leaf 0x16. In
+//! virtual machines, the leaf is usually missing, so the TSC frequency
+//! reported by the hypervisor timing leaf (0x40000010), or the calibrated one,
+//! is used as the base frequency instead.
+//!
+//! The effective frequency is measured with `APERF` and `MPERF`, whose ratio is
+//! the ratio of the effective frequency to the base frequency. The frequency
+//! is scaled with `IA32_PERF_CTL` on Intel CPUs that support Enhanced Intel
+//! SpeedStep, where the target performance state is the ratio of the target
+//! frequency to the bus frequency.
+
+use core::sync::atomic::{AtomicU64, Ordering};
+
+use spin::Once;
+use x86::{
+    cpuid::CpuId,
+    msr::{rdmsr, wrmsr},
+};
+
+use crate::{cpu::freq::FreqInfo, cpu_local, trap::DisabledLocalIrqGuard};
+
+const IA32_MPERF: u32 = 0xe7;
+const IA32_APERF: u32 = 0xe8;
+const MSR_PLATFORM_INFO: u32 = 0xce;
+const IA32_PERF_CTL: u32 = 0x199;
+
+/// The features of the CPUs that are related to the frequencies.
+struct Features {
+    has_aperf_mperf: bool,
+    /// The bus frequency in kHz, if the frequency can be scaled.
+    scaling_bus_khz: Option<u64>,
+}
+
+static FEATURES: Once<Features> = Once::new();
+
+cpu_local! {
+    static LAST_APERF: AtomicU64 = AtomicU64::new(0);
+    static LAST_MPERF: AtomicU64 = AtomicU64::new(0);
+}
+
+pub(crate) fn probe() -> FreqInfo {
+    let cpuid = CpuId::new();
+
+    let freq_info = cpuid.get_processor_frequency_info();
+    let base_khz = freq_info
+        .as_ref()
+        .map(|info| info.processor_base_frequency() as u64 * 1000)
+        .filter(|khz| *khz != 0)
+        .or_else(|| {
+            cpuid
+                .get_hypervisor_info()
+                .and_then(|info| info.tsc_frequency())
+                .map(|khz| khz as u64)
+                .filter(|khz| *khz != 0)
+        })
+        .unwrap_or_else(|| super::tsc_freq() / 1000);
+    let max_khz = freq_info
+        .as_ref()
+        .map(|info| info.processor_max_frequency() as u64 * 1000)
+        .filter(|khz| *khz >= base_khz)
+        .unwrap_or(base_khz);
+    let bus_khz = freq_info
+        .as_ref()
+        .map(|info| info.bus_frequency() as u64 * 1000)
+        .filter(|khz| *khz != 0);
+
+    let has_aperf_mperf = cpuid
+        .get_thermal_power_info()
+        .is_some_and(|info| info.has_hw_coord_feedback());
+    let is_intel = cpuid
+        .get_vendor_info()
+        .is_some_and(|vendor| vendor.as_str() == "GenuineIntel");
+    let has_eist = cpuid.get_feature_info().is_some_and(|info| info.has_eist());
+    let scaling_bus_khz = bus_khz.filter(|_| is_intel && has_eist);
+
+    FEATURES.call_once(|| Features {
+        has_aperf_mperf,
+        scaling_bus_khz,
+    });
+
+    // The minimum frequency is the maximum efficiency ratio in
+    // `MSR_PLATFORM_INFO`, which is only read if the frequency can be scaled.
+    let min_khz = scaling_bus_khz
+        .map(|bus_khz| {
+            // SAFETY: The CPUs that support Enhanced Intel SpeedStep have
+            // `MSR_PLATFORM_INFO`, and reading it has no side effects.
+            let platform_info = unsafe { rdmsr(MSR_PLATFORM_INFO) };
+            ((platform_info >> 40) & 0xff) * bus_khz
+        })
+        .filter(|khz| *khz != 0 && *khz <= base_khz)
+        .unwrap_or(base_khz);
+
+    FreqInfo {
+        min_khz,
+        base_khz,
+        max_khz,
+    }
+}
+
+/// Measures the effective frequency of the current CPU since the last call.
+pub(crate) fn measure_khz(guard: &DisabledLocalIrqGuard, info: &FreqInfo) -> Option<u64> {
+    if !FEATURES.get()?.has_aperf_mperf {
+        return None;
+    }
+
+    // SAFETY: Reading `APERF` and `MPERF` is supported as reported by CPUID,
+    // and it has no side effects.
+    let (aperf, mperf) = unsafe { (rdmsr(IA32_APERF), rdmsr(IA32_MPERF)) };
+    let last_aperf = LAST_APERF.get_with(guard).swap(aperf, Ordering::Relaxed);
+    let last_mperf = LAST_MPERF.get_with(guard).swap(mperf, Ordering::Relaxed);
+
+    let delta_aperf = aperf.wrapping_sub(last_aperf);
+    let delta_mperf = mperf.wrapping_sub(last_mperf);
+    if last_mperf == 0 || delta_mperf == 0 {
+        return None;
+    }
+
+    let khz = info.base_khz as u128 * delta_aperf as u128 / delta_mperf as u128;
+    Some(khz as u64)
+}
+
+pub(crate) fn can_scale() -> bool {
+    FEATURES
+        .get()
+        .is_some_and(|features| features.scaling_bus_khz.is_some())
+}
+
+pub(crate) fn driver_name() -> Option<&'static str> {
+    can_scale().then_some("speedstep")
+}
+
+/// Requests the current CPU to run at the target frequency.
+pub(crate) fn set_target_khz(_guard: &DisabledLocalIrqGuard, target_khz: u64) {
+    let Some(bus_khz) = FEATURES.get().and_then(|features| features.scaling_bus_khz) else {
+        return;
+    };
+
+    let ratio = (target_khz / bus_khz).clamp(1, 0xff);
+    // SAFETY: Enhanced Intel SpeedStep is supported as reported by CPUID, so
+    // the performance state can be requested with `IA32_PERF_CTL`, which does
+    // not affect memory safety.
+    unsafe { wrmsr(IA32_PERF_CTL, ratio << 8) };
+}
@@ -0,0 +1,140 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! The idle states of x86 CPUs.
+//!
+//! All CPUs can enter C1 with `HLT`. If `MONITOR`/`MWAIT` is supported and
+//! the C-states that it supports are enumerated by the CPUID leaf 5, the
+//! C-states are entered with `MWAIT` instead, which allows the deeper ones to
+//! be used.
+//!
+//! The CPUs do not report the exit latencies and the target residencies of the
+//! C-states. They should come from the ACPI `_CST` objects, which are not
+//! parsed yet, so the values of recent Intel CPUs in Linux's `intel_idle`
+//! driver are used as conservative estimates.
+
+use alloc::vec::Vec;
+use core::sync::atomic::AtomicU64;
+
+use spin::Once;
+use x86::cpuid::cpuid;
+
+use crate::{cpu::idle::IdleState, cpu_local, trap::DisabledLocalIrqGuard};
+
+/// The hint that means entering the state with `HLT`.
+const HLT_HINT: u32 = u32::MAX;
+
+/// The state entered with `HLT`.
+const HLT_STATE: IdleState = IdleState::new("C1", "HLT", 1_000, 1_000, HLT_HINT);
+
+/// The names, descriptions, exit latencies and target residencies of the
+/// `MWAIT` C-states C1 to C7.
+const MWAIT_STATES: [(&str, &str, u64, u64); 7] = [
+    ("C1", "MWAIT 0x00", 2_000, 2_000),
+    ("C2", "MWAIT 0x10", 20_000, 80_000),
+    ("C3", "MWAIT 0x20", 133_000, 600_000),
+    ("C4", "MWAIT 0x30", 166_000, 500_000),
+    ("C5", "MWAIT 0x40", 300_000, 900_000),
+    ("C6", "MWAIT 0x50", 600_000, 1_800_000),
+    ("C7", "MWAIT 0x60", 2_600_000, 7_700_000),
+];
+
+/// CPUID.01H:ECX.MONITOR[bit 3].
+const CPUID_1_ECX_MONITOR: u32 = 1 << 3;
+/// CPUID.01H:ECX[bit 31], which is set by the hypervisors.
+const CPUID_1_ECX_HYPERVISOR: u32 = 1 << 31;
+/// CPUID.05H:ECX[bit 0], which means that the MWAIT extensions are supported.
+const CPUID_5_ECX_EXTENSIONS: u32 = 1 << 0;
+/// CPUID.05H:ECX[bit 1], which means that the interrupts break `MWAIT` even
+/// if they are masked.
+const CPUID_5_ECX_INTERRUPT_BREAK: u32 = 1 << 1;
+
+static STATES: Once<Vec<IdleState>> = Once::new();
+
+cpu_local! {
+    /// The memory monitored by `MONITOR`, which is never written.
+    ///
+    /// The CPU is woken up by interrupts, including the IPIs for new work.
+    static MONITORED: AtomicU64 = AtomicU64::new(0);
+}
+
+pub(crate) fn init() {
+    STATES.call_once(|| {
+        let states = probe_mwait_states();
+        if states.is_empty() {
+            alloc::vec![HLT_STATE]
+        } else {
+            states
+        }
+    });
+}
+
+fn probe_mwait_states() -> Vec<IdleState> {
+    let mut states = Vec::new();
+
+    if cpuid!(1).ecx & CPUID_1_ECX_MONITOR == 0 || cpuid!(0).eax < 5 {
+        return states;
+    }
+    let leaf_5 = cpuid!(5);
+    let required = CPUID_5_ECX_EXTENSIONS | CPUID_5_ECX_INTERRUPT_BREAK;
+    if leaf_5.ecx & required != required {
+        return states;
+    }
+
+    // EDX[4n+3:4n] is the number of the sub-states of Cn, which are entered
+    // with the hint `((n - 1) << 4) | sub_state`.
+    for (index, (name, desc, exit_latency_ns, target_residency_ns)) in
+        MWAIT_STATES.into_iter().enumerate()
+    {
+        let num_sub_states = (leaf_5.edx >> ((index + 1) * 4)) & 0xf;
+        if num_sub_states == 0 {
+            continue;
+        }
+        let hint = (index as u32) << 4;
+        states.push(IdleState::new(
+            name,
+            desc,
+            exit_latency_ns,
+            target_residency_ns,
+            hint,
+        ));
+    }
+    states
+}
+
+pub(crate) fn states() -> &'static [IdleState] {
+    STATES.get().map_or(&[HLT_STATE], Vec::as_slice)
+}
+
+pub(crate) fn is_virtualized() -> bool {
+    cpuid!(1).ecx & CPUID_1_ECX_HYPERVISOR != 0
+}
+
+/// Enables local IRQs and enters the idle state until the next interrupt.
+pub(crate) fn enter_state(guard: &DisabledLocalIrqGuard, state: &IdleState) {
+    if state.hint == HLT_HINT {
+        super::irq::enable_local_and_halt();
+        return;
+    }
+
+    let monitored = MONITORED.get_with(guard).as_ptr();
+    // SAFETY: `MONITOR` and `MWAIT` only wait for the writes to the memory or
+    // the interrupts, which does not affect memory safety. Like `HLT`, the
+    // interrupt cannot be handled between `STI` and `MWAIT`, since `STI`
+    // takes effect after the next instruction.
+    unsafe {
+        core::arch::asm!(
+            "monitor",
+            in("rax") monitored,
+            in("ecx") 0,
+            in("edx") 0,
+            options(nostack, preserves_flags),
+        );
+        core::arch::asm!(
+            "sti",
+            "mwait",
+            in("eax") state.hint,
+            in("ecx") 0,
+            options(nostack),
+        );
+    }
+}
@@ -162,6 +162,14 @@ impl IrtEntry {
         self.0 = 0b11 | (vector as u128) << 16;
     }
 
+    /// Sets the APIC ID of the processor that the interrupt is directed to.
+    ///
+    /// The table is always in the x2APIC mode, where the ID occupies bits 63:32.
+    pub fn set_destination_id(&mut self, apic_id: u32) {
+        const DST_MASK: u128 = 0xFFFF_FFFF << 32;
+        self.0 = (self.0 & !DST_MASK) | (apic_id as u128) << 32;
+    }
+
     pub fn source_validation_type(&self) -> SourceValidationType {
         const SVT_MASK: u128 = 0x3 << 82;
         SourceValidationType::try_from(((self.0 & SVT_MASK) >> 82) as u32).unwrap()
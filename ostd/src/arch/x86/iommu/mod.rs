@@ -9,7 +9,9 @@ mod invalidate;
 mod registers;
 
 pub(crate) use dma_remapping::{has_dma_remapping, map, unmap};
-pub(crate) use interrupt_remapping::{alloc_irt_entry, has_interrupt_remapping, IrtEntryHandle};
+pub(crate) use interrupt_remapping::{
+    alloc_irt_entry, has_interrupt_remapping, invalidate_irt_entry_cache, IrtEntryHandle,
+};
 
 use crate::mm::page_table::PageTableError;
 
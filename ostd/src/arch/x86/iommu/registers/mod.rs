@@ -137,25 +137,7 @@ impl IommuRegisters {
         self.write_global_command(GlobalCommand::IRE, true);
         while !self.read_global_status().contains(GlobalStatus::IRES) {}
 
-        // Invalidate interrupt cache
-        if self.read_global_status().contains(GlobalStatus::QIES) {
-            let mut queue = QUEUE.get().unwrap().lock();
-
-            // Construct global invalidation of interrupt cache and invalidation wait.
-            queue.append_descriptor(InterruptEntryCache::global_invalidation().0);
-            let tail = queue.tail();
-            self.invalidate.queue_tail.write((tail << 4) as u64);
-            while (self.invalidate.queue_head.read() >> 4) + 1 == tail as u64 {}
-
-            // We need to set the interrupt flag so that the `Invalidation Completion Status Register` can report the completion status.
-            queue.append_descriptor(InvalidationWait::with_interrupt_flag().0);
-            self.invalidate.queue_tail.write((queue.tail() << 4) as u64);
-
-            // Wait for completion
-            while self.invalidate.completion_status.read() == 0 {}
-        } else {
-            self.global_invalidation()
-        }
+        self.invalidate_interrupt_entry_cache();
 
         // Disable Compatibility format interrupts
         if self.read_global_status().contains(GlobalStatus::CFIS) {
@@ -164,6 +146,34 @@ impl IommuRegisters {
         }
     }
 
+    /// Invalidates the cached interrupt remapping table entries.
+    ///
+    /// This must be called after a present entry is modified.
+    pub(super) fn invalidate_interrupt_entry_cache(&mut self) {
+        if !self.read_global_status().contains(GlobalStatus::QIES) {
+            self.global_invalidation();
+            return;
+        }
+
+        let mut queue = QUEUE.get().unwrap().lock();
+
+        // The completion status is sticky, so clear it (by writing 1) before waiting.
+        self.invalidate.completion_status.write(1);
+
+        // Construct global invalidation of interrupt cache and invalidation wait.
+        queue.append_descriptor(InterruptEntryCache::global_invalidation().0);
+        let tail = queue.tail();
+        self.invalidate.queue_tail.write((tail << 4) as u64);
+        while (self.invalidate.queue_head.read() >> 4) + 1 == tail as u64 {}
+
+        // We need to set the interrupt flag so that the `Invalidation Completion Status Register` can report the completion status.
+        queue.append_descriptor(InvalidationWait::with_interrupt_flag().0);
+        self.invalidate.queue_tail.write((queue.tail() << 4) as u64);
+
+        // Wait for completion
+        while self.invalidate.completion_status.read() == 0 {}
+    }
+
     pub(super) fn enable_queued_invalidation(&mut self, queue: &Queue) {
         assert!(self
             .read_extended_capability()
@@ -5,16 +5,21 @@
 #![allow(dead_code)]
 
 use alloc::{boxed::Box, fmt::Debug, sync::Arc, vec::Vec};
+use core::sync::atomic::{AtomicU32, Ordering};
 
 use id_alloc::IdAlloc;
 use spin::Once;
 use x86_64::registers::rflags::{self, RFlags};
 
-use super::iommu::{alloc_irt_entry, has_interrupt_remapping, IrtEntryHandle};
+use super::{
+    iommu::{alloc_irt_entry, has_interrupt_remapping, invalidate_irt_entry_cache, IrtEntryHandle},
+    trap::COMPAT_SYSCALL_VECTOR,
+};
 use crate::{
     cpu::CpuId,
     sync::{LocalIrqDisabled, Mutex, PreemptDisabled, RwLock, RwLockReadGuard, SpinLock},
     trap::TrapFrame,
+    Error, Result,
 };
 
 /// The global allocator for software defined IRQ lines.
@@ -29,6 +34,8 @@ pub(crate) fn init() {
             irq_num: i as u8,
             callback_list: RwLock::new(Vec::new()),
             bind_remapping_entry: Once::new(),
+            affinity: AtomicU32::new(CpuId::bsp().as_usize() as u32),
+            retarget_fn: SpinLock::new(None),
         });
     }
     IRQ_LIST.call_once(|| list);
@@ -42,6 +49,10 @@ pub(crate) fn init() {
         for i in 0..32 {
             id_alloc.alloc_specific(i).unwrap();
         }
+        // The vector of `int 0x80` is used by the 32-bit system calls.
+        id_alloc
+            .alloc_specific(COMPAT_SYSCALL_VECTOR as usize)
+            .unwrap();
         SpinLock::new(id_alloc)
     });
 }
@@ -58,6 +69,14 @@ pub(crate) fn disable_local() {
     x86_64::instructions::interrupts::disable();
 }
 
+/// Enables local IRQs and halts the CPU until the next interrupt.
+///
+/// An interrupt cannot be handled between enabling IRQs and halting, since
+/// the STI instruction takes effect after the next instruction.
+pub(crate) fn enable_local_and_halt() {
+    x86_64::instructions::interrupts::enable_and_hlt();
+}
+
 pub(crate) fn is_local_enabled() -> bool {
     (rflags::read_raw() & RFlags::INTERRUPT_FLAG.bits()) != 0
 }
@@ -83,12 +102,33 @@ impl Debug for CallbackElement {
     }
 }
 
+/// The function that reprograms an interrupt source to deliver the IRQ to a CPU.
+pub(crate) type RetargetFn = dyn Fn(CpuId) -> Result<()> + Send + Sync;
+
 /// An interrupt request (IRQ) line.
-#[derive(Debug)]
 pub(crate) struct IrqLine {
     pub(crate) irq_num: u8,
     pub(crate) callback_list: RwLock<Vec<CallbackElement>>,
     bind_remapping_entry: Once<Arc<SpinLock<IrtEntryHandle, LocalIrqDisabled>>>,
+    /// The ID of the CPU that the IRQ is delivered to.
+    affinity: AtomicU32,
+    /// The function that reprograms the interrupt source of the IRQ, e.g., an I/O APIC
+    /// redirection entry or an MSI-X table entry.
+    ///
+    /// It is not used if the IRQ is remapped, in which case the remapping table entry
+    /// is updated instead.
+    retarget_fn: SpinLock<Option<Arc<RetargetFn>>, LocalIrqDisabled>,
+}
+
+impl Debug for IrqLine {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("IrqLine")
+            .field("irq_num", &self.irq_num)
+            .field("callback_list", &self.callback_list)
+            .field("bind_remapping_entry", &self.bind_remapping_entry)
+            .field("affinity", &self.affinity)
+            .finish_non_exhaustive()
+    }
 }
 
 impl IrqLine {
@@ -119,6 +159,63 @@ impl IrqLine {
         self.irq_num
     }
 
+    /// Returns the CPU that the IRQ is delivered to.
+    pub fn affinity(&self) -> CpuId {
+        CpuId::try_from(self.affinity.load(Ordering::Acquire) as usize).unwrap()
+    }
+
+    /// Delivers the IRQ to a CPU.
+    ///
+    /// The caller must serialize the calls to this method.
+    pub fn set_affinity(&self, cpu_id: CpuId) -> Result<()> {
+        // Without interrupt remapping, the I/O APIC and MSI messages can only be delivered
+        // to the CPUs with 8-bit APIC IDs.
+        if self.bind_remapping_entry().is_none() && cpu_id.as_usize() > u8::MAX as usize {
+            return Err(Error::InvalidArgs);
+        }
+
+        let old_affinity = self
+            .affinity
+            .swap(cpu_id.as_usize() as u32, Ordering::AcqRel);
+
+        // The new affinity is stored before the interrupt source is reprogrammed. So an
+        // interrupt source that is set up concurrently either uses the new affinity or has
+        // its `retarget_fn` called here.
+        let result = if let Some(handle) = self.bind_remapping_entry() {
+            let mut handle = handle.lock();
+            handle
+                .irt_entry_mut()
+                .unwrap()
+                .set_destination_id(cpu_id.as_usize() as u32);
+            invalidate_irt_entry_cache();
+            Ok(())
+        } else {
+            let retarget_fn = self.retarget_fn.lock().clone();
+            retarget_fn.map_or(Ok(()), |retarget_fn| retarget_fn(cpu_id))
+        };
+
+        if result.is_err() {
+            self.affinity.store(old_affinity, Ordering::Release);
+        }
+        result
+    }
+
+    /// Sets the function that reprograms the interrupt source of the IRQ.
+    ///
+    /// The function is called when the affinity of the IRQ is changed. The interrupt
+    /// source should be programmed with the current [`Self::affinity`] after this method
+    /// is called.
+    pub fn set_retarget_fn(&self, retarget_fn: Option<Arc<RetargetFn>>) {
+        *self.retarget_fn.lock() = retarget_fn;
+    }
+
+    /// Resets the affinity after the IRQ line is freed.
+    pub(crate) fn reset_affinity(&self) {
+        self.set_retarget_fn(None);
+        self.affinity
+            .store(CpuId::bsp().as_usize() as u32, Ordering::Release);
+    }
+
     pub fn callback_list(
         &self,
     ) -> RwLockReadGuard<alloc::vec::Vec<CallbackElement>, PreemptDisabled> {
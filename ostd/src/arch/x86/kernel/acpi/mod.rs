@@ -3,7 +3,9 @@
 #![allow(unused_variables)]
 
 pub mod dmar;
+pub mod power;
 pub mod remapping;
+pub mod srat;
 
 use core::ptr::NonNull;
 
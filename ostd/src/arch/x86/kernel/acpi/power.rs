@@ -0,0 +1,487 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! ACPI power management with the fixed hardware.
+//!
+//! This module implements the features of the ACPI fixed hardware that are needed to turn off
+//! and reset the machine, and to receive the power button events:
+//!  - The machine is turned off by entering the S5 (soft-off) sleep state. The values written to
+//!    the `SLP_TYP` fields are found in the `\_S5_` object of the DSDT.
+//!  - The machine is reset by writing the reset value to the reset register in the FADT.
+//!  - The power button is the fixed power button, whose events are signaled by the SCI.
+//!
+//! Reference: ACPI Specification 6.5, Section 4.8 and Section 5.2.9.
+
+use core::mem::size_of;
+
+use acpi::{
+    sdt::{SdtHeader, Signature},
+    AcpiTable,
+};
+use log::{info, warn};
+use spin::Once;
+
+use crate::{
+    arch::x86::{
+        device::io_port::{IoPort, ReadWriteAccess, WriteOnlyAccess},
+        kernel::IO_APIC,
+    },
+    mm::{paddr_to_vaddr, Paddr},
+    sync::SpinLock,
+    trap::{IrqLine, TrapFrame},
+};
+
+/// The Fixed ACPI Description Table (FADT), which is also known as the `FACP`.
+///
+/// Only the fields defined since ACPI 1.0 are included, and the fields added by later revisions
+/// are read according to the length of the table.
+#[derive(Debug, Clone, Copy)]
+#[repr(C, packed)]
+#[allow(dead_code)]
+struct Fadt {
+    header: SdtHeader,
+    firmware_ctrl: u32,
+    dsdt: u32,
+    reserved1: u8,
+    preferred_pm_profile: u8,
+    sci_int: u16,
+    smi_cmd: u32,
+    acpi_enable: u8,
+    acpi_disable: u8,
+    s4bios_req: u8,
+    pstate_cnt: u8,
+    pm1a_evt_blk: u32,
+    pm1b_evt_blk: u32,
+    pm1a_cnt_blk: u32,
+    pm1b_cnt_blk: u32,
+    pm2_cnt_blk: u32,
+    pm_tmr_blk: u32,
+    gpe0_blk: u32,
+    gpe1_blk: u32,
+    pm1_evt_len: u8,
+    pm1_cnt_len: u8,
+    pm2_cnt_len: u8,
+    pm_tmr_len: u8,
+    gpe0_blk_len: u8,
+    gpe1_blk_len: u8,
+    gpe1_base: u8,
+    cst_cnt: u8,
+    p_lvl2_lat: u16,
+    p_lvl3_lat: u16,
+    flush_size: u16,
+    flush_stride: u16,
+    duty_offset: u8,
+    duty_width: u8,
+    day_alrm: u8,
+    mon_alrm: u8,
+    century: u8,
+    iapc_boot_arch: u16,
+    reserved2: u8,
+    flags: u32,
+}
+
+// SAFETY: The `Fadt` is the beginning of the FADT as described in the ACPI specification.
+unsafe impl AcpiTable for Fadt {
+    const SIGNATURE: Signature = Signature::FADT;
+    fn header(&self) -> &SdtHeader {
+        &self.header
+    }
+}
+
+/// The Generic Address Structure (GAS).
+#[derive(Debug, Clone, Copy)]
+#[repr(C, packed)]
+#[allow(dead_code)]
+struct GenericAddress {
+    address_space: u8,
+    bit_width: u8,
+    bit_offset: u8,
+    access_size: u8,
+    address: u64,
+}
+
+/// The offset of the reset register in the FADT, which is added in ACPI 2.0.
+const RESET_REG_OFFSET: usize = 116;
+/// The offset of the reset value in the FADT.
+const RESET_VALUE_OFFSET: usize = 128;
+/// The offset of the 64-bit address of the DSDT in the FADT, which is added in ACPI 2.0.
+const X_DSDT_OFFSET: usize = 140;
+
+/// The flag indicating that the power button is a control method device instead of a fixed
+/// feature.
+const FADT_PWR_BUTTON: u32 = 1 << 4;
+/// The flag indicating that the reset register is supported.
+const FADT_RESET_REG_SUP: u32 = 1 << 10;
+
+const ADDRESS_SPACE_SYSTEM_MEMORY: u8 = 0;
+const ADDRESS_SPACE_SYSTEM_IO: u8 = 1;
+
+/// The power button status in the `PM1_STS` register, and the enable bit in `PM1_EN`.
+const PWRBTN: u16 = 1 << 8;
+/// The bit in `PM1_CNT` that indicates whether the power management events are delivered as
+/// SCIs (the ACPI mode) or SMIs (the legacy mode).
+const SCI_EN: u16 = 1 << 0;
+const SLP_TYP_SHIFT: u16 = 10;
+const SLP_TYP_MASK: u16 = 0b111 << SLP_TYP_SHIFT;
+const SLP_EN: u16 = 1 << 13;
+
+/// The power management registers and the information to use them.
+struct PowerManagement {
+    sci_irq: u8,
+    smi_cmd: u16,
+    acpi_enable: u8,
+    pm1a_evt_blk: u16,
+    pm1b_evt_blk: Option<u16>,
+    pm1_evt_len: u8,
+    pm1a_cnt_blk: u16,
+    pm1b_cnt_blk: Option<u16>,
+    has_fixed_power_button: bool,
+    reset: Option<(GenericAddress, u8)>,
+    /// The values of `SLP_TYPa` and `SLP_TYPb` for the S5 sleep state.
+    s5_sleep_types: Option<(u8, u8)>,
+}
+
+static POWER_MANAGEMENT: Once<PowerManagement> = Once::new();
+
+/// The IRQ line of the SCI, which is kept alive to receive the power button events.
+static SCI_IRQ: Once<SpinLock<IrqLine>> = Once::new();
+
+/// Parses the FADT and the DSDT, and enables the power button events.
+pub(in crate::arch) fn init() {
+    let Some(pm) = PowerManagement::new() else {
+        warn!("[ACPI]: The power management registers are not found");
+        return;
+    };
+    let pm = POWER_MANAGEMENT.call_once(|| pm);
+
+    if pm.s5_sleep_types.is_none() {
+        warn!("[ACPI]: The S5 sleep state is not found, powering off is not supported");
+    }
+    if !pm.has_fixed_power_button {
+        info!("[ACPI]: The power button is not a fixed feature device");
+        return;
+    }
+
+    pm.enable_acpi_mode();
+
+    let Some(io_apics) = IO_APIC.get() else {
+        warn!("[ACPI]: The I/O APIC is not available, the power button is not supported");
+        return;
+    };
+    let mut irq = IrqLine::alloc().unwrap();
+    // Like other legacy ISA interrupts, the SCI is assumed to be identity-mapped to the pins of
+    // the first I/O APIC.
+    if let Err(err) = io_apics[0].lock().enable(pm.sci_irq, irq.clone()) {
+        warn!("[ACPI]: Failed to enable the SCI: {:?}", err);
+        return;
+    }
+    irq.on_active(handle_sci);
+    SCI_IRQ.call_once(|| SpinLock::new(irq));
+
+    // Clear the stale events before enabling them.
+    pm.write_pm1_status(PWRBTN);
+    pm.write_pm1_enable(pm.read_pm1_enable() | PWRBTN);
+
+    info!("[ACPI]: The power button is enabled on IRQ {}", pm.sci_irq);
+}
+
+/// Enters the S5 sleep state to turn off the machine.
+///
+/// This method returns only if the machine does not support the S5 sleep state.
+pub(in crate::arch) fn enter_s5() {
+    let Some(pm) = POWER_MANAGEMENT.get() else {
+        return;
+    };
+    let Some((slp_typa, slp_typb)) = pm.s5_sleep_types else {
+        return;
+    };
+
+    pm.enable_acpi_mode();
+
+    // Like Linux, `SLP_TYP` is written before `SLP_EN` to work around buggy hardware.
+    let write_sleep_type = |port: u16, slp_typ: u8| {
+        // SAFETY: The port is the PM1 control register described by the FADT.
+        let control = unsafe { IoPort::<u16, ReadWriteAccess>::new(port) };
+        let value = (control.read() & !(SLP_TYP_MASK | SLP_EN))
+            | ((slp_typ as u16) << SLP_TYP_SHIFT) & SLP_TYP_MASK;
+        control.write(value);
+        value
+    };
+    let value_a = write_sleep_type(pm.pm1a_cnt_blk, slp_typa);
+    let value_b = pm
+        .pm1b_cnt_blk
+        .map(|port| (port, write_sleep_type(port, slp_typb)));
+
+    // SAFETY: The ports are the PM1 control registers described by the FADT.
+    unsafe {
+        IoPort::<u16, ReadWriteAccess>::new(pm.pm1a_cnt_blk).write(value_a | SLP_EN);
+        if let Some((port, value_b)) = value_b {
+            IoPort::<u16, ReadWriteAccess>::new(port).write(value_b | SLP_EN);
+        }
+    }
+}
+
+/// Resets the machine with the reset register.
+///
+/// This method returns only if the reset register is not supported or does not work.
+pub(in crate::arch) fn reset() {
+    let Some((reset_reg, reset_value)) = POWER_MANAGEMENT.get().and_then(|pm| pm.reset) else {
+        return;
+    };
+
+    let address = reset_reg.address;
+    match reset_reg.address_space {
+        ADDRESS_SPACE_SYSTEM_IO => {
+            // SAFETY: The port is the reset register described by the FADT.
+            unsafe { IoPort::<u8, WriteOnlyAccess>::new(address as u16).write(reset_value) };
+        }
+        ADDRESS_SPACE_SYSTEM_MEMORY => {
+            // SAFETY: The address is the reset register described by the FADT.
+            unsafe {
+                (paddr_to_vaddr(address as Paddr) as *mut u8).write_volatile(reset_value);
+            }
+        }
+        // The reset register in the PCI configuration space is rarely used.
+        address_space => {
+            warn!(
+                "[ACPI]: The reset register in the address space {} is not supported",
+                address_space
+            );
+        }
+    }
+}
+
+fn handle_sci(_trap_frame: &TrapFrame) {
+    let pm = POWER_MANAGEMENT.get().unwrap();
+
+    let status = pm.read_pm1_status();
+    if status & PWRBTN == 0 {
+        return;
+    }
+    // The status bits are cleared by writing ones.
+    pm.write_pm1_status(PWRBTN);
+
+    crate::power::handle_power_button();
+}
+
+impl PowerManagement {
+    fn new() -> Option<Self> {
+        let acpi_tables = super::ACPI_TABLES.get()?.lock();
+        let fadt_mapping = acpi_tables.find_table::<Fadt>().ok()?;
+        let fadt = *fadt_mapping;
+        let fadt_start = fadt_mapping.physical_start();
+        let fadt_len = fadt.header.length as usize;
+        drop(acpi_tables);
+
+        if fadt.pm1a_evt_blk == 0 || fadt.pm1a_cnt_blk == 0 {
+            return None;
+        }
+
+        let reset = if fadt.flags & FADT_RESET_REG_SUP != 0
+            && let Some(reset_reg) = read_fadt_field(fadt_start, fadt_len, RESET_REG_OFFSET)
+            && let Some(reset_value) = read_fadt_field(fadt_start, fadt_len, RESET_VALUE_OFFSET)
+        {
+            Some((reset_reg, reset_value))
+        } else {
+            None
+        };
+
+        let dsdt = match read_fadt_field::<u64>(fadt_start, fadt_len, X_DSDT_OFFSET) {
+            Some(x_dsdt) if x_dsdt != 0 => x_dsdt as Paddr,
+            _ => fadt.dsdt as Paddr,
+        };
+        let s5_sleep_types = if dsdt != 0 {
+            // SAFETY: The DSDT is provided by the firmware and begins with a valid header.
+            let dsdt_len = unsafe {
+                core::ptr::read_unaligned(paddr_to_vaddr(dsdt) as *const SdtHeader).length
+            } as usize;
+            // SAFETY: The AML code follows the header, and the length is the length of the table
+            // minus the size of the header.
+            let aml = unsafe {
+                core::slice::from_raw_parts(
+                    paddr_to_vaddr(dsdt + size_of::<SdtHeader>()) as *const u8,
+                    dsdt_len.saturating_sub(size_of::<SdtHeader>()),
+                )
+            };
+            find_s5_sleep_types(aml)
+        } else {
+            None
+        };
+
+        Some(Self {
+            sci_irq: fadt.sci_int as u8,
+            smi_cmd: fadt.smi_cmd as u16,
+            acpi_enable: fadt.acpi_enable,
+            pm1a_evt_blk: fadt.pm1a_evt_blk as u16,
+            pm1b_evt_blk: (fadt.pm1b_evt_blk != 0).then_some(fadt.pm1b_evt_blk as u16),
+            pm1_evt_len: fadt.pm1_evt_len,
+            pm1a_cnt_blk: fadt.pm1a_cnt_blk as u16,
+            pm1b_cnt_blk: (fadt.pm1b_cnt_blk != 0).then_some(fadt.pm1b_cnt_blk as u16),
+            has_fixed_power_button: fadt.flags & FADT_PWR_BUTTON == 0,
+            reset,
+            s5_sleep_types,
+        })
+    }
+
+    /// Switches from the legacy mode to the ACPI mode if the firmware has not done so.
+    fn enable_acpi_mode(&self) {
+        // SAFETY: The port is the PM1 control register described by the FADT.
+        let control = unsafe { IoPort::<u16, ReadWriteAccess>::new(self.pm1a_cnt_blk) };
+        if control.read() & SCI_EN != 0 {
+            return;
+        }
+        // The hardware supports only the ACPI mode if the SMI command port is zero.
+        if self.smi_cmd == 0 || self.acpi_enable == 0 {
+            return;
+        }
+
+        // SAFETY: The port is the SMI command port described by the FADT.
+        unsafe { IoPort::<u8, WriteOnlyAccess>::new(self.smi_cmd).write(self.acpi_enable) };
+
+        // The transition may take some time, so wait for a while.
+        const MAX_POLLS: usize = 1 << 20;
+        for _ in 0..MAX_POLLS {
+            if control.read() & SCI_EN != 0 {
+                return;
+            }
+            core::hint::spin_loop();
+        }
+        warn!("[ACPI]: Failed to switch to the ACPI mode");
+    }
+
+    /// Reads the `PM1_STS` register, which is the first half of the PM1 event block.
+    fn read_pm1_status(&self) -> u16 {
+        self.pm1_evt_ports(0)
+            // SAFETY: The port is the PM1 status register described by the FADT.
+            .map(|port| unsafe { IoPort::<u16, ReadWriteAccess>::new(port) }.read())
+            .fold(0, |status, value| status | value)
+    }
+
+    fn write_pm1_status(&self, value: u16) {
+        for port in self.pm1_evt_ports(0) {
+            // SAFETY: The port is the PM1 status register described by the FADT.
+            unsafe { IoPort::<u16, ReadWriteAccess>::new(port) }.write(value);
+        }
+    }
+
+    /// Reads the `PM1_EN` register, which is the second half of the PM1 event block.
+    fn read_pm1_enable(&self) -> u16 {
+        let offset = self.pm1_evt_len as u16 / 2;
+        // SAFETY: The port is the PM1 enable register described by the FADT.
+        unsafe { IoPort::<u16, ReadWriteAccess>::new(self.pm1a_evt_blk + offset) }.read()
+    }
+
+    fn write_pm1_enable(&self, value: u16) {
+        let offset = self.pm1_evt_len as u16 / 2;
+        for port in self.pm1_evt_ports(offset) {
+            // SAFETY: The port is the PM1 enable register described by the FADT.
+            unsafe { IoPort::<u16, ReadWriteAccess>::new(port) }.write(value);
+        }
+    }
+
+    fn pm1_evt_ports(&self, offset: u16) -> impl Iterator<Item = u16> {
+        core::iter::once(self.pm1a_evt_blk)
+            .chain(self.pm1b_evt_blk)
+            .map(move |port| port + offset)
+    }
+}
+
+/// Reads a field of the FADT that may be absent in the tables of early revisions.
+fn read_fadt_field<T: Copy>(fadt_start: Paddr, fadt_len: usize, offset: usize) -> Option<T> {
+    if offset + size_of::<T>() > fadt_len {
+        return None;
+    }
+
+    // SAFETY: The field is within the FADT, and any bit pattern is valid for the field types.
+    Some(unsafe { core::ptr::read_unaligned(paddr_to_vaddr(fadt_start + offset) as *const T) })
+}
+
+// The AML opcodes used to find the `\_S5_` object.
+const ZERO_OP: u8 = 0x00;
+const ONE_OP: u8 = 0x01;
+const NAME_OP: u8 = 0x08;
+const BYTE_PREFIX: u8 = 0x0a;
+const WORD_PREFIX: u8 = 0x0b;
+const DWORD_PREFIX: u8 = 0x0c;
+const PACKAGE_OP: u8 = 0x12;
+const ROOT_CHAR: u8 = b'\\';
+
+/// Finds the sleep types of the S5 sleep state in the AML code of the DSDT.
+///
+/// The `\_S5_` object is a package of integers, whose first two elements are the values of
+/// `SLP_TYPa` and `SLP_TYPb`. Instead of interpreting the whole AML code, this function looks for
+/// the definition of the object (i.e., `Name (_S5, Package () { ... })`), which is how the object
+/// is defined by all known firmware.
+fn find_s5_sleep_types(aml: &[u8]) -> Option<(u8, u8)> {
+    let mut start = 0;
+    while let Some(pos) = aml[start..].windows(4).position(|name| name == b"_S5_") {
+        let name_pos = start + pos;
+        start = name_pos + 4;
+
+        let is_name_def = match name_pos {
+            0 => false,
+            1 => aml[0] == NAME_OP,
+            _ => {
+                aml[name_pos - 1] == NAME_OP
+                    || (aml[name_pos - 1] == ROOT_CHAR && aml[name_pos - 2] == NAME_OP)
+            }
+        };
+        if !is_name_def {
+            continue;
+        }
+
+        if let Some(sleep_types) = parse_s5_package(&aml[start..]) {
+            return Some(sleep_types);
+        }
+    }
+
+    None
+}
+
+fn parse_s5_package(aml: &[u8]) -> Option<(u8, u8)> {
+    let mut bytes = aml.iter().copied();
+    if bytes.next()? != PACKAGE_OP {
+        return None;
+    }
+
+    // The two most significant bits of the lead byte are the number of the following bytes of
+    // the package length.
+    let pkg_len_lead = bytes.next()?;
+    for _ in 0..(pkg_len_lead >> 6) {
+        bytes.next()?;
+    }
+
+    let num_elements = bytes.next()?;
+    if num_elements == 0 {
+        return None;
+    }
+
+    let mut parse_integer = || -> Option<u8> {
+        let value = match bytes.next()? {
+            ZERO_OP => 0,
+            ONE_OP => 1,
+            BYTE_PREFIX => bytes.next()?,
+            // The sleep types have only three bits, so the higher bytes are ignored.
+            WORD_PREFIX => {
+                let low_byte = bytes.next()?;
+                bytes.nth(0)?;
+                low_byte
+            }
+            DWORD_PREFIX => {
+                let low_byte = bytes.next()?;
+                bytes.nth(2)?;
+                low_byte
+            }
+            _ => return None,
+        };
+        Some(value)
+    };
+
+    let slp_typa = parse_integer()?;
+    let slp_typb = if num_elements >= 2 {
+        parse_integer()?
+    } else {
+        slp_typa
+    };
+
+    Some((slp_typa, slp_typb))
+}
@@ -0,0 +1,223 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! The System Resource Affinity Table (SRAT).
+//!
+//! The SRAT associates the processors and the memory ranges with the proximity domains, which
+//! are the NUMA nodes in the ACPI terminology.
+//!
+//! Reference: ACPI Specification 6.5, Section 5.2.16.
+
+use alloc::vec::Vec;
+use core::{mem::size_of, ops::Range};
+
+use acpi::{
+    sdt::{SdtHeader, Signature},
+    AcpiTable,
+};
+
+use crate::mm::{paddr_to_vaddr, Paddr};
+
+/// The System Resource Affinity Table (SRAT).
+#[derive(Debug)]
+pub struct Srat {
+    memory_affinities: Vec<MemoryAffinity>,
+    processor_affinities: Vec<ProcessorAffinity>,
+}
+
+/// A memory range that belongs to a proximity domain.
+#[derive(Debug, Clone)]
+pub struct MemoryAffinity {
+    pub range: Range<Paddr>,
+    pub proximity_domain: u32,
+}
+
+/// A processor that belongs to a proximity domain.
+#[derive(Debug, Clone, Copy)]
+pub struct ProcessorAffinity {
+    /// The (x2)APIC ID of the processor.
+    pub apic_id: u32,
+    pub proximity_domain: u32,
+}
+
+#[derive(Debug, Clone, Copy)]
+#[repr(C)]
+#[allow(dead_code)]
+struct SratHeader {
+    header: SdtHeader,
+    reserved1: u32,
+    reserved2: u64,
+}
+
+// SAFETY: The `SratHeader` is the header of the SRAT as described in the ACPI specification.
+unsafe impl AcpiTable for SratHeader {
+    const SIGNATURE: Signature = Signature::SRAT;
+    fn header(&self) -> &SdtHeader {
+        &self.header
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+#[repr(C, packed)]
+#[allow(dead_code)]
+struct LocalApicAffinityEntry {
+    typ: u8,
+    length: u8,
+    proximity_domain_low: u8,
+    apic_id: u8,
+    flags: u32,
+    local_sapic_eid: u8,
+    proximity_domain_high: [u8; 3],
+    clock_domain: u32,
+}
+
+#[derive(Debug, Clone, Copy)]
+#[repr(C, packed)]
+#[allow(dead_code)]
+struct MemoryAffinityEntry {
+    typ: u8,
+    length: u8,
+    proximity_domain: u32,
+    reserved1: u16,
+    base_address_low: u32,
+    base_address_high: u32,
+    length_low: u32,
+    length_high: u32,
+    reserved2: u32,
+    flags: u32,
+    reserved3: u64,
+}
+
+#[derive(Debug, Clone, Copy)]
+#[repr(C, packed)]
+#[allow(dead_code)]
+struct X2ApicAffinityEntry {
+    typ: u8,
+    length: u8,
+    reserved1: u16,
+    proximity_domain: u32,
+    x2apic_id: u32,
+    flags: u32,
+    clock_domain: u32,
+    reserved2: u32,
+}
+
+const LOCAL_APIC_AFFINITY: u8 = 0;
+const MEMORY_AFFINITY: u8 = 1;
+const X2APIC_AFFINITY: u8 = 2;
+
+/// The flag indicating that the entry is enabled, which is the same for all kinds of entries.
+const FLAG_ENABLED: u32 = 1 << 0;
+
+impl Srat {
+    /// Parses the SRAT from the ACPI tables.
+    ///
+    /// This method returns `None` if the ACPI tables are not initialized or the platform does
+    /// not provide the SRAT.
+    pub fn new() -> Option<Self> {
+        let acpi_tables = super::ACPI_TABLES.get()?.lock();
+        let srat_mapping = acpi_tables.find_table::<SratHeader>().ok()?;
+
+        let start = srat_mapping.physical_start() + size_of::<SratHeader>();
+        let len = srat_mapping.mapped_length() - size_of::<SratHeader>();
+        // SAFETY: The entries follow the header, and the length is the length of the table
+        // minus the size of the header.
+        let entries =
+            unsafe { core::slice::from_raw_parts(paddr_to_vaddr(start) as *const u8, len) };
+
+        let mut srat = Self {
+            memory_affinities: Vec::new(),
+            processor_affinities: Vec::new(),
+        };
+
+        let mut offset = 0;
+        // Common header: type: u8, length: u8
+        while offset + 2 <= entries.len() {
+            let typ = entries[offset];
+            let length = entries[offset + 1] as usize;
+            if length < 2 || offset + length > entries.len() {
+                break;
+            }
+            let entry = &entries[offset..offset + length];
+            offset += length;
+
+            match typ {
+                LOCAL_APIC_AFFINITY => {
+                    // SAFETY: The bytes are long enough and any bit pattern is valid.
+                    let Some(entry) = (unsafe { read_entry::<LocalApicAffinityEntry>(entry) })
+                    else {
+                        continue;
+                    };
+                    if entry.flags & FLAG_ENABLED == 0 {
+                        continue;
+                    }
+                    let [high0, high1, high2] = entry.proximity_domain_high;
+                    srat.processor_affinities.push(ProcessorAffinity {
+                        apic_id: entry.apic_id as u32,
+                        proximity_domain: u32::from_le_bytes([
+                            entry.proximity_domain_low,
+                            high0,
+                            high1,
+                            high2,
+                        ]),
+                    });
+                }
+                MEMORY_AFFINITY => {
+                    // SAFETY: The bytes are long enough and any bit pattern is valid.
+                    let Some(entry) = (unsafe { read_entry::<MemoryAffinityEntry>(entry) }) else {
+                        continue;
+                    };
+                    if entry.flags & FLAG_ENABLED == 0 {
+                        continue;
+                    }
+                    let base =
+                        (entry.base_address_high as usize) << 32 | entry.base_address_low as usize;
+                    let len = (entry.length_high as usize) << 32 | entry.length_low as usize;
+                    srat.memory_affinities.push(MemoryAffinity {
+                        range: base..base + len,
+                        proximity_domain: entry.proximity_domain,
+                    });
+                }
+                X2APIC_AFFINITY => {
+                    // SAFETY: The bytes are long enough and any bit pattern is valid.
+                    let Some(entry) = (unsafe { read_entry::<X2ApicAffinityEntry>(entry) }) else {
+                        continue;
+                    };
+                    if entry.flags & FLAG_ENABLED == 0 {
+                        continue;
+                    }
+                    srat.processor_affinities.push(ProcessorAffinity {
+                        apic_id: entry.x2apic_id,
+                        proximity_domain: entry.proximity_domain,
+                    });
+                }
+                // Other entries (e.g., for GICs and generic initiators) are not used.
+                _ => {}
+            }
+        }
+
+        Some(srat)
+    }
+
+    /// Returns the enabled memory affinity entries.
+    pub fn memory_affinities(&self) -> &[MemoryAffinity] {
+        &self.memory_affinities
+    }
+
+    /// Returns the enabled processor affinity entries.
+    pub fn processor_affinities(&self) -> &[ProcessorAffinity] {
+        &self.processor_affinities
+    }
+}
+
+/// Reads an entry of type `T` from `bytes`.
+///
+/// # Safety
+///
+/// Any bit pattern must be valid for `T`.
+unsafe fn read_entry<T: Copy>(bytes: &[u8]) -> Option<T> {
+    if bytes.len() < size_of::<T>() {
+        return None;
+    }
+    // SAFETY: The bytes are long enough, and the caller guarantees that the value is valid.
+    Some(unsafe { core::ptr::read_unaligned(bytes.as_ptr() as *const T) })
+}
@@ -2,7 +2,7 @@
 
 #![allow(dead_code)]
 
-use alloc::{vec, vec::Vec};
+use alloc::{sync::Arc, vec, vec::Vec};
 
 use acpi::PlatformInfo;
 use bit_field::BitField;
@@ -12,6 +12,7 @@ use spin::Once;
 
 use crate::{
     arch::{iommu::has_interrupt_remapping, x86::kernel::acpi::ACPI_TABLES},
+    cpu::CpuId,
     mm::paddr_to_vaddr,
     sync::SpinLock,
     trap::IrqLine,
@@ -56,6 +57,7 @@ impl IoApic {
             // Enable irt entry
             let irt_entry_mut = handle.irt_entry_mut().unwrap();
             irt_entry_mut.enable_default(irq.num() as u32);
+            irt_entry_mut.set_destination_id(irq.inner_irq().affinity().as_usize() as u32);
 
             // Construct remappable format RTE with RTE[48] set.
             let mut value: u64 = irq.num() as u64 | 0x1_0000_0000_0000;
@@ -78,13 +80,32 @@ impl IoApic {
             return Ok(());
         }
 
+        // The I/O APICs are identified by their indexes in `IO_APIC` during initialization.
+        let io_apic_index = self.id() as usize;
+        irq.inner_irq()
+            .set_retarget_fn(Some(Arc::new(move |cpu_id| {
+                let mut io_apic = IO_APIC.get().unwrap()[io_apic_index].lock();
+                io_apic.set_destination(index, cpu_id)
+            })));
+        let destination = irq.inner_irq().affinity();
+
+        self.set_destination(index, destination).unwrap();
         self.access
             .write(Self::TABLE_REG_BASE + 2 * index, irq.num() as u32);
-        self.access.write(Self::TABLE_REG_BASE + 2 * index + 1, 0);
         self.irqs.push(irq);
         Ok(())
     }
 
+    /// Sets the CPU that an entry delivers its interrupt to.
+    ///
+    /// The entry uses the physical destination mode, where only 8-bit APIC IDs are supported.
+    fn set_destination(&mut self, index: u8, cpu_id: CpuId) -> Result<()> {
+        let apic_id = u8::try_from(cpu_id.as_usize()).map_err(|_| Error::InvalidArgs)?;
+        self.access
+            .write(Self::TABLE_REG_BASE + 2 * index + 1, (apic_id as u32) << 24);
+        Ok(())
+    }
+
     /// Disables an entry. The index should not exceed the `max_redirection_entry`
     pub fn disable(&mut self, index: u8) -> Result<()> {
         if index >= self.max_redirection_entry() {
@@ -95,7 +116,13 @@ impl IoApic {
         // mask interrupt
         self.access.write(Self::TABLE_REG_BASE + 2 * index, 1 << 16);
         self.access.write(Self::TABLE_REG_BASE + 2 * index + 1, 0);
-        self.irqs.retain(|h| h.num() != irq_num);
+        self.irqs.retain(|h| {
+            if h.num() != irq_num {
+                return true;
+            }
+            h.inner_irq().set_retarget_fn(None);
+            false
+        });
         Ok(())
     }
 
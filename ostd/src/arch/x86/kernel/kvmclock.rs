@@ -0,0 +1,253 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! The paravirtual clocks of KVM.
+//!
+//! With kvm-clock, the host maintains the time since the guest boots up in memory shared with
+//! the guest, along with the TSC at that time and the scale to convert the TSC to the time.
+//! The host also reports its real time when kvm-clock is zero, i.e., the boot wall clock.
+//!
+//! With the clock pairing hypercall, the host reports its real time and the TSC of the guest
+//! at the same instant, which allows precise synchronization between the guest and the host,
+//! e.g., by the PTP clock of KVM.
+//!
+//! Reference: <https://docs.kernel.org/virt/kvm/x86/msr.html>
+
+use core::{
+    arch::asm,
+    sync::atomic::{fence, Ordering},
+    time::Duration,
+};
+
+use ostd_pod::Pod;
+use spin::Once;
+use x86::{
+    cpuid::{cpuid, CpuId, Hypervisor},
+    msr::wrmsr,
+};
+
+use crate::{
+    mm::{Frame, FrameAllocOptions, VmIo},
+    sync::{LocalIrqDisabled, SpinLock},
+};
+
+const KVM_CPUID_FEATURES: u32 = 0x4000_0001;
+const KVM_FEATURE_CLOCKSOURCE2: u32 = 1 << 3;
+const KVM_FEATURE_CLOCKSOURCE_STABLE_BIT: u32 = 1 << 24;
+
+const MSR_KVM_WALL_CLOCK_NEW: u32 = 0x4b56_4d00;
+const MSR_KVM_SYSTEM_TIME_NEW: u32 = 0x4b56_4d01;
+/// The bit in `MSR_KVM_SYSTEM_TIME_NEW` that enables kvm-clock.
+const KVM_SYSTEM_TIME_ENABLE: u64 = 1;
+
+const KVM_HC_CLOCK_PAIRING: u64 = 9;
+const KVM_CLOCK_PAIRING_WALLCLOCK: u64 = 0;
+
+/// The offsets of the shared structures in the shared frame.
+const TIME_INFO_OFFSET: usize = 0;
+const WALL_CLOCK_OFFSET: usize = 0x40;
+const CLOCK_PAIRING_OFFSET: usize = 0x80;
+
+/// The time information of a vCPU, i.e., `struct pvclock_vcpu_time_info` in Linux.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Pod)]
+struct PvclockVcpuTimeInfo {
+    version: u32,
+    pad0: u32,
+    tsc_timestamp: u64,
+    system_time: u64,
+    tsc_to_system_mul: u32,
+    tsc_shift: i8,
+    flags: u8,
+    pad: [u8; 2],
+}
+
+/// The boot wall clock, i.e., `struct pvclock_wall_clock` in Linux.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Pod)]
+struct PvclockWallClock {
+    version: u32,
+    sec: u32,
+    nsec: u32,
+}
+
+/// The result of the clock pairing hypercall, i.e., `struct kvm_clock_pairing` in Linux.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Pod)]
+struct KvmClockPairing {
+    sec: i64,
+    nsec: i64,
+    tsc: u64,
+    flags: u32,
+    pad: [u32; 9],
+}
+
+/// The real time of the host and the TSC of the guest at the same instant.
+#[derive(Debug, Clone, Copy)]
+pub struct ClockPairing {
+    /// The real time of the host.
+    pub realtime: Duration,
+    /// The TSC of the guest.
+    pub tsc: u64,
+}
+
+struct KvmClock {
+    /// The frame shared with the host, which contains the structures written by the host.
+    frame: Frame<()>,
+    is_amd: bool,
+}
+
+static KVM_CLOCK: Once<Option<KvmClock>> = Once::new();
+
+static PAIRING_LOCK: SpinLock<(), LocalIrqDisabled> = SpinLock::new(());
+
+fn kvm_clock() -> Option<&'static KvmClock> {
+    KVM_CLOCK.call_once(init).as_ref()
+}
+
+fn init() -> Option<KvmClock> {
+    cfg_if::cfg_if! {
+        if #[cfg(feature = "cvm_guest")] {
+            // The host cannot write the private memory of a trust domain.
+            if ::tdx_guest::tdx_is_enabled() {
+                return None;
+            }
+        }
+    }
+
+    let cpuid = CpuId::new();
+    if cpuid.get_hypervisor_info()?.identify() != Hypervisor::KVM {
+        return None;
+    }
+    // Since kvm-clock is only registered on the CPU that initializes it, the clock must be
+    // consistent across CPUs to be read on any CPU.
+    let features = cpuid!(KVM_CPUID_FEATURES).eax;
+    let required_features = KVM_FEATURE_CLOCKSOURCE2 | KVM_FEATURE_CLOCKSOURCE_STABLE_BIT;
+    if features & required_features != required_features {
+        return None;
+    }
+
+    let is_amd = cpuid
+        .get_vendor_info()
+        .is_some_and(|vendor| matches!(vendor.as_str(), "AuthenticAMD" | "HygonGenuine"));
+    let frame = FrameAllocOptions::new().alloc_frame().ok()?;
+    let paddr = frame.start_paddr() as u64;
+
+    // SAFETY: The MSRs are supported as reported by CPUID. The frame is allocated for the host
+    // to write the structures, and it is never deallocated.
+    unsafe {
+        wrmsr(
+            MSR_KVM_SYSTEM_TIME_NEW,
+            (paddr + TIME_INFO_OFFSET as u64) | KVM_SYSTEM_TIME_ENABLE,
+        );
+        wrmsr(MSR_KVM_WALL_CLOCK_NEW, paddr + WALL_CLOCK_OFFSET as u64);
+    }
+
+    Some(KvmClock { frame, is_amd })
+}
+
+/// Returns whether kvm-clock is available.
+pub fn is_available() -> bool {
+    kvm_clock().is_some()
+}
+
+/// Reads the time of kvm-clock, i.e., the time since the guest boots up maintained by the host.
+pub fn read_time() -> Option<Duration> {
+    let clock = kvm_clock()?;
+    let (info, tsc) =
+        read_consistent::<PvclockVcpuTimeInfo>(clock, TIME_INFO_OFFSET, crate::arch::read_tsc);
+
+    let delta = tsc.saturating_sub(info.tsc_timestamp);
+    let delta = if info.tsc_shift >= 0 {
+        delta << info.tsc_shift
+    } else {
+        delta >> -info.tsc_shift
+    };
+    let delta_nanos = (delta as u128 * info.tsc_to_system_mul as u128) >> 32;
+    Some(Duration::from_nanos(info.system_time + delta_nanos as u64))
+}
+
+/// Returns the real time of the host when kvm-clock is zero.
+pub fn boot_wall_clock() -> Option<Duration> {
+    let clock = kvm_clock()?;
+    let (wall_clock, _) = read_consistent::<PvclockWallClock>(clock, WALL_CLOCK_OFFSET, || ());
+    Some(Duration::new(wall_clock.sec as u64, wall_clock.nsec))
+}
+
+/// Reads a structure written by the host, whose first field is a version that is odd while
+/// the host is updating the structure.
+///
+/// `f` is called while the structure is consistent, e.g., to read the TSC.
+fn read_consistent<T: Pod, R>(clock: &KvmClock, offset: usize, f: impl Fn() -> R) -> (T, R) {
+    loop {
+        let version = clock.frame.read_val::<u32>(offset).unwrap();
+        fence(Ordering::Acquire);
+        let val = clock.frame.read_val::<T>(offset).unwrap();
+        let ret = f();
+        fence(Ordering::Acquire);
+        if version % 2 == 0 && clock.frame.read_val::<u32>(offset).unwrap() == version {
+            return (val, ret);
+        }
+        core::hint::spin_loop();
+    }
+}
+
+/// Gets the real time of the host and the TSC of the guest at the same instant.
+///
+/// It returns `None` if the host does not support the clock pairing, e.g., when the TSC of the
+/// host is not stable.
+pub fn clock_pairing() -> Option<ClockPairing> {
+    let clock = kvm_clock()?;
+    // The frame is shared by all the CPUs, so the hypercalls are serialized.
+    let _guard = PAIRING_LOCK.lock();
+
+    let paddr = clock.frame.start_paddr() as u64 + CLOCK_PAIRING_OFFSET as u64;
+    let ret = hypercall2(
+        clock.is_amd,
+        KVM_HC_CLOCK_PAIRING,
+        paddr,
+        KVM_CLOCK_PAIRING_WALLCLOCK,
+    );
+    if ret != 0 {
+        return None;
+    }
+
+    let pairing = clock
+        .frame
+        .read_val::<KvmClockPairing>(CLOCK_PAIRING_OFFSET)
+        .unwrap();
+    Some(ClockPairing {
+        realtime: Duration::new(pairing.sec as u64, pairing.nsec as u32),
+        tsc: pairing.tsc,
+    })
+}
+
+/// Makes a KVM hypercall with two arguments and returns the result.
+fn hypercall2(is_amd: bool, nr: u64, arg0: u64, arg1: u64) -> i64 {
+    let ret: u64;
+    // SAFETY: The hypercall only writes the memory that is shared with the host. The `rbx`
+    // register, which cannot be used as an operand, is saved and restored.
+    unsafe {
+        if is_amd {
+            asm!(
+                "xchg {arg0}, rbx",
+                "vmmcall",
+                "xchg {arg0}, rbx",
+                arg0 = inout(reg) arg0 => _,
+                inout("rax") nr => ret,
+                in("rcx") arg1,
+                options(nostack),
+            );
+        } else {
+            asm!(
+                "xchg {arg0}, rbx",
+                "vmcall",
+                "xchg {arg0}, rbx",
+                arg0 = inout(reg) arg0 => _,
+                inout("rax") nr => ret,
+                in("rcx") arg1,
+                options(nostack),
+            );
+        }
+    }
+    ret as i64
+}
@@ -2,6 +2,7 @@
 
 pub(super) mod acpi;
 pub(super) mod apic;
+pub mod kvmclock;
 pub(super) mod pic;
 pub(super) mod tsc;
 
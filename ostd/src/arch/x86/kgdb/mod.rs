@@ -0,0 +1,459 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! An in-kernel GDB stub (kgdb).
+//!
+//! The stub lets GDB debug the kernel through the serial port with the GDB remote serial
+//! protocol. It is enabled by the `ostd.kgdb=MODE` kernel command line argument, where the
+//! mode is one of:
+//! - `on`: The stub is entered when a breakpoint is hit, when the kernel panics, or when GDB
+//!   interrupts the kernel (e.g., by pressing Ctrl-C);
+//! - `wait`: Like `on`, and the stub is also entered when OSTD is initialized, so that GDB
+//!   can attach before the kernel starts running.
+//!
+//! When the stub is entered on a CPU, the other CPUs are halted until GDB resumes the kernel.
+//! GDB sees the kernel as a single thread, which is the interrupted context on the CPU that
+//! entered the stub. The registers, the kernel memory and the software breakpoints (which GDB
+//! inserts by writing the memory) are supported, as well as single-stepping.
+//!
+//! The serial port is shared with the console, so the console output should be kept quiet
+//! (e.g., with the `log_level=error` kernel command line argument) while GDB is attached.
+//! Debugging through a virtio console is not supported.
+
+mod packet;
+
+use core::{
+    cell::SyncUnsafeCell,
+    fmt::Write,
+    mem::size_of,
+    sync::atomic::{AtomicBool, AtomicUsize, Ordering},
+};
+
+use spin::Once;
+use x86_64::registers::rflags::RFlags;
+
+use self::packet::{
+    decode_hex, parse_hex, recv_packet, send_packet, Reply, INTERRUPT, MAX_PACKET_SIZE,
+};
+use super::{irq::send_ipi, read_tsc, tsc_freq};
+use crate::{
+    boot::EARLY_INFO,
+    cpu::{all_cpus, num_cpus, CpuId, PinCurrentCpu},
+    cpu_local_cell, early_println,
+    mm::{
+        kspace::KERNEL_PAGE_TABLE, paddr_to_vaddr, page_prop::CachePolicy, Vaddr,
+        MAX_USERSPACE_VADDR,
+    },
+    trap::{self, IrqLine, TrapFrame},
+};
+
+/// The number of the general-purpose registers, including `rip`, in the register packets.
+const NUM_GPRS: usize = 17;
+
+/// The signal reported to GDB when the kernel stops, which is `SIGTRAP`.
+const STOP_REPLY: &[u8] = b"S05";
+
+/// The owner of the session when no CPU is in the stub.
+const NO_OWNER: usize = usize::MAX;
+
+static IS_ENABLED: AtomicBool = AtomicBool::new(false);
+
+/// Whether GDB is attached, in which case a stop reply is sent when the stub is entered.
+static IS_ATTACHED: AtomicBool = AtomicBool::new(false);
+
+/// The ID of the CPU that is in the stub.
+static SESSION_OWNER: AtomicUsize = AtomicUsize::new(NO_OWNER);
+
+/// The number of CPUs that are halted while another CPU is in the stub.
+static NUM_PARKED: AtomicUsize = AtomicUsize::new(0);
+
+/// The IRQ line that halts the other CPUs when the stub is entered.
+static ROUNDUP_IRQ: Once<IrqLine> = Once::new();
+
+/// The buffers of the session, which are not allocated on the heap or on the stack, since the
+/// heap may be locked by a halted CPU and the stack of the interrupted context may be small.
+static BUFFERS: SyncUnsafeCell<Buffers> = SyncUnsafeCell::new(Buffers {
+    packet: [0; MAX_PACKET_SIZE],
+    reply: Reply::new(),
+});
+
+struct Buffers {
+    packet: [u8; MAX_PACKET_SIZE],
+    reply: Reply,
+}
+
+cpu_local_cell! {
+    /// Whether the CPU is single-stepping for GDB.
+    static IS_STEPPING: bool = false;
+    /// Whether the local IRQs are enabled before single-stepping.
+    static STEP_IRQ_ENABLED: bool = false;
+}
+
+/// How the kernel resumes when GDB leaves the stub.
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Resume {
+    Continue,
+    Step,
+}
+
+/// Initializes the stub from the kernel command line.
+pub(crate) fn init() {
+    let kcmdline = EARLY_INFO.get().unwrap().kernel_cmdline;
+    let should_wait = match kcmdline
+        .split(' ')
+        .find(|arg| arg.starts_with("ostd.kgdb="))
+        .map(|arg| arg.split('=').last().unwrap_or_default())
+    {
+        Some("on") => false,
+        Some("wait") => true,
+        _ => return,
+    };
+
+    let mut irq = IrqLine::alloc().unwrap();
+    irq.on_active(|_trap_frame: &TrapFrame| park());
+    ROUNDUP_IRQ.call_once(|| irq);
+    IS_ENABLED.store(true, Ordering::Release);
+
+    if should_wait {
+        breakpoint();
+    }
+}
+
+/// Returns whether the stub is enabled.
+pub fn is_enabled() -> bool {
+    IS_ENABLED.load(Ordering::Acquire)
+}
+
+/// Enters the stub, if it is enabled, as if a breakpoint is hit at the caller.
+pub fn breakpoint() {
+    if is_enabled() {
+        // SAFETY: The breakpoint is handled by `handle_breakpoint`, which resumes the
+        // execution after the `int3` instruction.
+        unsafe { core::arch::asm!("int3") };
+    }
+}
+
+/// Handles a byte of the console input, returning `true` if it is the interrupt from GDB.
+pub(crate) fn handle_console_input(byte: u8) -> bool {
+    if byte != INTERRUPT || !is_enabled() {
+        return false;
+    }
+    breakpoint();
+    true
+}
+
+/// Handles a breakpoint in the kernel mode.
+///
+/// This function returns `false` if the stub is not enabled.
+pub(super) fn handle_breakpoint(f: &mut TrapFrame) -> bool {
+    if !is_enabled() {
+        return false;
+    }
+    enter(f);
+    true
+}
+
+/// Handles a debug exception in the kernel mode.
+///
+/// This function returns `false` if the exception is not caused by single-stepping for GDB.
+pub(super) fn handle_debug(f: &mut TrapFrame) -> bool {
+    if !IS_STEPPING.load() {
+        return false;
+    }
+    IS_STEPPING.store(false);
+    f.rflags &= !(RFlags::TRAP_FLAG.bits() as usize);
+    if STEP_IRQ_ENABLED.load() {
+        f.rflags |= RFlags::INTERRUPT_FLAG.bits() as usize;
+    }
+    enter(f);
+    true
+}
+
+fn enter(f: &mut TrapFrame) {
+    let irq_guard = trap::disable_local();
+    let this_cpu = irq_guard.current_cpu();
+
+    while SESSION_OWNER
+        .compare_exchange(
+            NO_OWNER,
+            this_cpu.as_usize(),
+            Ordering::Acquire,
+            Ordering::Relaxed,
+        )
+        .is_err()
+    {
+        // Another CPU is in the stub, which may be waiting for this CPU to be halted.
+        park();
+    }
+    halt_other_cpus(this_cpu);
+
+    // SAFETY: The buffers are only accessed by the owner of the session.
+    let buffers = unsafe { &mut *BUFFERS.get() };
+    if serve(f, buffers) == Resume::Step {
+        // Step with the local IRQs disabled, so that the next instruction is not an IRQ
+        // handler. The IRQ flag is restored by `handle_debug`.
+        IS_STEPPING.store(true);
+        STEP_IRQ_ENABLED.store(f.rflags & RFlags::INTERRUPT_FLAG.bits() as usize != 0);
+        f.rflags &= !(RFlags::INTERRUPT_FLAG.bits() as usize);
+        f.rflags |= RFlags::TRAP_FLAG.bits() as usize;
+    }
+
+    SESSION_OWNER.store(NO_OWNER, Ordering::Release);
+}
+
+/// Halts the other CPUs until the session ends.
+///
+/// The CPUs are interrupted with IPIs. A CPU that does not respond in a second, e.g., because
+/// it runs with the local IRQs disabled, keeps running.
+fn halt_other_cpus(this_cpu: CpuId) {
+    let irq_num = ROUNDUP_IRQ.get().unwrap().num();
+    for cpu in all_cpus().filter(|cpu| *cpu != this_cpu) {
+        // SAFETY: The IRQ handler only halts the CPU until the session ends.
+        unsafe { send_ipi(cpu, irq_num) };
+    }
+
+    let deadline = read_tsc() + tsc_freq();
+    while NUM_PARKED.load(Ordering::Acquire) < num_cpus() - 1 && read_tsc() < deadline {
+        core::hint::spin_loop();
+    }
+}
+
+/// Halts the current CPU while another CPU is in the stub.
+fn park() {
+    NUM_PARKED.fetch_add(1, Ordering::AcqRel);
+    while SESSION_OWNER.load(Ordering::Acquire) != NO_OWNER {
+        core::hint::spin_loop();
+    }
+    NUM_PARKED.fetch_sub(1, Ordering::AcqRel);
+}
+
+/// Serves the commands from GDB until GDB resumes the kernel.
+fn serve(f: &mut TrapFrame, buffers: &mut Buffers) -> Resume {
+    if IS_ATTACHED.load(Ordering::Relaxed) {
+        send_packet(STOP_REPLY);
+    } else {
+        early_println!("[kgdb] Waiting for GDB to attach to the serial port");
+    }
+
+    loop {
+        let len = recv_packet(&mut buffers.packet);
+        IS_ATTACHED.store(true, Ordering::Relaxed);
+
+        let reply = &mut buffers.reply;
+        reply.clear();
+        let Some((&command, args)) = buffers.packet[..len].split_first() else {
+            send_packet(reply.as_bytes());
+            continue;
+        };
+        match command {
+            b'?' => reply.push_bytes(STOP_REPLY),
+            b'g' => read_registers(f, reply),
+            b'G' => write_registers(f, args, reply),
+            b'm' => read_memory(args, reply),
+            b'M' => write_memory(args, reply),
+            b'c' | b's' => {
+                if !args.is_empty() {
+                    let Some(addr) = parse_hex(args) else {
+                        send_packet(b"E01");
+                        continue;
+                    };
+                    f.rip = addr;
+                }
+                return if command == b's' {
+                    Resume::Step
+                } else {
+                    Resume::Continue
+                };
+            }
+            b'D' => {
+                IS_ATTACHED.store(false, Ordering::Relaxed);
+                send_packet(b"OK");
+                return Resume::Continue;
+            }
+            b'k' => {
+                IS_ATTACHED.store(false, Ordering::Relaxed);
+                return Resume::Continue;
+            }
+            b'q' => handle_query(args, reply),
+            b'H' | b'T' => reply.push_bytes(b"OK"),
+            // An empty reply means that the command is not supported.
+            _ => {}
+        }
+        send_packet(reply.as_bytes());
+    }
+}
+
+fn handle_query(args: &[u8], reply: &mut Reply) {
+    if args.starts_with(b"Supported") {
+        write!(reply, "PacketSize={:x}", MAX_PACKET_SIZE).unwrap();
+        return;
+    }
+    match args {
+        b"C" => reply.push_bytes(b"QC1"),
+        b"fThreadInfo" => reply.push_bytes(b"m1"),
+        b"sThreadInfo" => reply.push_bytes(b"l"),
+        b"Attached" => reply.push_bytes(b"1"),
+        _ => {}
+    }
+}
+
+/// Returns the stack pointer and the stack segment of the interrupted context.
+fn interrupted_stack(f: &TrapFrame) -> (usize, usize) {
+    // SAFETY: The CPU always pushes the stack pointer and the stack segment of the
+    // interrupted context right after `rflags`, which is the last field of the trap frame.
+    unsafe {
+        let stack = (&f.rflags as *const usize).add(1);
+        (*stack, *stack.add(1))
+    }
+}
+
+fn read_registers(f: &TrapFrame, reply: &mut Reply) {
+    let (rsp, ss) = interrupted_stack(f);
+    let gprs: [usize; NUM_GPRS] = [
+        f.rax, f.rbx, f.rcx, f.rdx, f.rsi, f.rdi, f.rbp, rsp, f.r8, f.r9, f.r10, f.r11, f.r12,
+        f.r13, f.r14, f.r15, f.rip,
+    ];
+    for gpr in gprs {
+        reply.push_hex(&(gpr as u64).to_le_bytes());
+    }
+    // The data segment registers are not used in the 64-bit mode.
+    for reg in [f.rflags, f.cs, ss, 0, 0, 0, 0] {
+        reply.push_hex(&(reg as u32).to_le_bytes());
+    }
+}
+
+/// Writes the general-purpose registers.
+///
+/// The stack pointer, the flags and the segment registers cannot be written.
+fn write_registers(f: &mut TrapFrame, args: &[u8], reply: &mut Reply) {
+    let mut bytes = [0u8; NUM_GPRS * size_of::<u64>()];
+    if args
+        .get(..bytes.len() * 2)
+        .and_then(|digits| decode_hex(digits, &mut bytes))
+        .is_none()
+    {
+        reply.push_bytes(b"E01");
+        return;
+    }
+
+    let gprs: [usize; NUM_GPRS] = core::array::from_fn(|i| {
+        let gpr = &bytes[i * size_of::<u64>()..(i + 1) * size_of::<u64>()];
+        u64::from_le_bytes(gpr.try_into().unwrap()) as usize
+    });
+    [
+        f.rax,
+        f.rbx,
+        f.rcx,
+        f.rdx,
+        f.rsi,
+        f.rdi,
+        f.rbp,
+        _,
+        f.r8,
+        f.r9,
+        f.r10,
+        f.r11,
+        f.r12,
+        f.r13,
+        f.r14,
+        f.r15,
+        f.rip,
+    ] = gprs;
+    reply.push_bytes(b"OK");
+}
+
+fn read_memory(args: &[u8], reply: &mut Reply) {
+    let Some((addr, len)) = parse_addr_len(args) else {
+        reply.push_bytes(b"E01");
+        return;
+    };
+
+    // Each byte is replied in two hexadecimal digits.
+    for offset in 0..len.min(MAX_PACKET_SIZE / 2) {
+        let Some(alias) = addr.checked_add(offset).and_then(linear_alias) else {
+            // Reply the bytes before the first inaccessible byte, if there are any.
+            if offset == 0 {
+                reply.push_bytes(b"E14");
+            }
+            return;
+        };
+        // SAFETY: The byte is in the normal memory, which is mapped by the linear mapping.
+        let byte = unsafe { (alias as *const u8).read_volatile() };
+        reply.push_hex(&[byte]);
+    }
+}
+
+fn write_memory(args: &[u8], reply: &mut Reply) {
+    let parsed = args.iter().position(|byte| *byte == b':').and_then(|pos| {
+        let (addr, len) = parse_addr_len(&args[..pos])?;
+        Some((addr, len, &args[pos + 1..]))
+    });
+    let mut bytes = [0u8; MAX_PACKET_SIZE / 2];
+    let Some((addr, len)) = parsed.and_then(|(addr, len, digits)| {
+        (decode_hex(digits, &mut bytes)? == len).then_some((addr, len))
+    }) else {
+        reply.push_bytes(b"E01");
+        return;
+    };
+
+    let is_accessible =
+        (0..len).all(|offset| addr.checked_add(offset).and_then(linear_alias).is_some());
+    if !is_accessible {
+        reply.push_bytes(b"E14");
+        return;
+    }
+    for (offset, byte) in bytes[..len].iter().enumerate() {
+        // The kernel code may not be mapped as writable, so write through the linear mapping.
+        let alias = linear_alias(addr + offset).unwrap();
+        // SAFETY: The byte is in the normal memory, which is mapped by the linear mapping.
+        // GDB is trusted to write the kernel memory.
+        unsafe { (alias as *mut u8).write_volatile(*byte) };
+    }
+    reply.push_bytes(b"OK");
+}
+
+/// Parses `<addr>,<len>` in hexadecimal digits.
+fn parse_addr_len(args: &[u8]) -> Option<(Vaddr, usize)> {
+    let pos = args.iter().position(|byte| *byte == b',')?;
+    Some((parse_hex(&args[..pos])?, parse_hex(&args[pos + 1..])?))
+}
+
+/// Returns the address in the linear mapping of the byte at the kernel address.
+///
+/// This function returns `None` if the byte is not mapped, or is not in the normal memory.
+fn linear_alias(addr: Vaddr) -> Option<Vaddr> {
+    if addr < MAX_USERSPACE_VADDR {
+        return None;
+    }
+    let (paddr, prop) = KERNEL_PAGE_TABLE.get()?.query(addr)?;
+    (prop.cache == CachePolicy::Writeback).then(|| paddr_to_vaddr(paddr))
+}
+
+#[cfg(ktest)]
+mod test {
+    use ostd_macros::ktest;
+
+    use super::*;
+
+    #[ktest]
+    fn parse_memory_args() {
+        assert_eq!(parse_addr_len(b"ffff8000,10"), Some((0xffff8000, 0x10)));
+        assert_eq!(parse_addr_len(b"ffff8000"), None);
+        assert_eq!(parse_addr_len(b"ffff800g,10"), None);
+
+        let mut bytes = [0u8; 4];
+        assert_eq!(decode_hex(b"c3cc", &mut bytes), Some(2));
+        assert_eq!(bytes[..2], [0xc3, 0xcc]);
+        assert_eq!(decode_hex(b"c3c", &mut bytes), None);
+        assert_eq!(decode_hex(b"0011223344", &mut bytes), None);
+    }
+
+    #[ktest]
+    fn access_kernel_memory() {
+        static DATA: [u8; 4] = [1, 2, 3, 4];
+        let addr = DATA.as_ptr() as Vaddr;
+        let alias = linear_alias(addr).unwrap();
+        // SAFETY: The alias maps the same memory as `DATA`.
+        assert_eq!(unsafe { *(alias as *const [u8; 4]) }, DATA);
+        assert_eq!(linear_alias(0x1000), None);
+    }
+}
@@ -0,0 +1,156 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! The packets of the GDB remote serial protocol.
+//!
+//! A packet is `$<data>#<checksum>`, where the checksum is the sum of the data bytes modulo
+//! 256 in two hexadecimal digits. The receiver acknowledges a packet with `+`, or requests a
+//! retransmission with `-`.
+//!
+//! Reference: <https://sourceware.org/gdb/current/onlinedocs/gdb.html/Remote-Protocol.html>
+
+use core::fmt;
+
+use crate::arch::serial;
+
+/// The maximum size of the data in a packet.
+pub(super) const MAX_PACKET_SIZE: usize = 4096;
+
+/// The byte that GDB sends to interrupt the target.
+pub(super) const INTERRUPT: u8 = 0x03;
+
+/// Receives a packet, returning the length of its data in the buffer.
+///
+/// The bytes outside of a packet are ignored, and the excess data of a packet that is too
+/// long are discarded.
+pub(super) fn recv_packet(buf: &mut [u8; MAX_PACKET_SIZE]) -> usize {
+    loop {
+        while recv_byte() != b'$' {}
+
+        let mut len = 0;
+        let mut checksum = 0u8;
+        loop {
+            let byte = recv_byte();
+            if byte == b'#' {
+                break;
+            }
+            if len < buf.len() {
+                buf[len] = byte;
+                len += 1;
+            }
+            checksum = checksum.wrapping_add(byte);
+        }
+
+        let expected = hex_digit(recv_byte())
+            .zip(hex_digit(recv_byte()))
+            .map(|(high, low)| high << 4 | low);
+        if expected == Some(checksum) {
+            serial::send(b'+');
+            return len;
+        }
+        serial::send(b'-');
+    }
+}
+
+/// Sends a packet, retransmitting it until it is acknowledged.
+pub(super) fn send_packet(data: &[u8]) {
+    let checksum = data.iter().fold(0u8, |sum, byte| sum.wrapping_add(*byte));
+    loop {
+        serial::send(b'$');
+        for byte in data {
+            serial::send(*byte);
+        }
+        serial::send(b'#');
+        for digit in to_hex(checksum) {
+            serial::send(digit);
+        }
+
+        if recv_byte() == b'+' {
+            return;
+        }
+    }
+}
+
+fn recv_byte() -> u8 {
+    loop {
+        if let Some(byte) = serial::receive_char() {
+            return byte;
+        }
+        core::hint::spin_loop();
+    }
+}
+
+/// A reply packet that is being built.
+///
+/// The reply is truncated if it exceeds the maximum size of a packet. The buffer is not
+/// allocated on the heap, since the heap may be locked by a halted CPU.
+pub(super) struct Reply {
+    buf: [u8; MAX_PACKET_SIZE],
+    len: usize,
+}
+
+impl Reply {
+    pub(super) const fn new() -> Self {
+        Self {
+            buf: [0; MAX_PACKET_SIZE],
+            len: 0,
+        }
+    }
+
+    pub(super) fn clear(&mut self) {
+        self.len = 0;
+    }
+
+    pub(super) fn as_bytes(&self) -> &[u8] {
+        &self.buf[..self.len]
+    }
+
+    pub(super) fn push_bytes(&mut self, bytes: &[u8]) {
+        let len = bytes.len().min(self.buf.len() - self.len);
+        self.buf[self.len..self.len + len].copy_from_slice(&bytes[..len]);
+        self.len += len;
+    }
+
+    /// Appends the bytes in hexadecimal digits.
+    pub(super) fn push_hex(&mut self, bytes: &[u8]) {
+        for byte in bytes {
+            self.push_bytes(&to_hex(*byte));
+        }
+    }
+}
+
+impl fmt::Write for Reply {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        self.push_bytes(s.as_bytes());
+        Ok(())
+    }
+}
+
+const HEX_DIGITS: &[u8; 16] = b"0123456789abcdef";
+
+fn to_hex(byte: u8) -> [u8; 2] {
+    [
+        HEX_DIGITS[(byte >> 4) as usize],
+        HEX_DIGITS[(byte & 0xf) as usize],
+    ]
+}
+
+fn hex_digit(byte: u8) -> Option<u8> {
+    (byte as char).to_digit(16).map(|digit| digit as u8)
+}
+
+/// Parses a number in hexadecimal digits.
+pub(super) fn parse_hex(digits: &[u8]) -> Option<usize> {
+    let digits = core::str::from_utf8(digits).ok()?;
+    usize::from_str_radix(digits, 16).ok()
+}
+
+/// Decodes the hexadecimal digits into the buffer, returning the number of bytes decoded.
+pub(super) fn decode_hex(digits: &[u8], buf: &mut [u8]) -> Option<usize> {
+    if digits.len() % 2 != 0 || digits.len() / 2 > buf.len() {
+        return None;
+    }
+    for (byte, pair) in buf.iter_mut().zip(digits.chunks_exact(2)) {
+        *byte = hex_digit(pair[0])? << 4 | hex_digit(pair[1])?;
+    }
+    Some(digits.len() / 2)
+}
@@ -0,0 +1,292 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! Kernel probes (kprobes).
+//!
+//! A kprobe calls its handler whenever a kernel function is entered, without rebuilding the
+//! kernel. Registering a kprobe replaces the first byte of the function with an `int3`
+//! instruction. When the breakpoint is hit, the handler is called with the trap frame, whose
+//! instruction pointer is the address of the function and whose registers hold the arguments.
+//! Then the replaced instruction is executed out of line, and the execution resumes in the
+//! function.
+//!
+//! Only a function that starts with `push rbp`, which is the case for all the functions if the
+//! kernel is built with frame pointers, can be probed. The handler of a kprobe is not called
+//! if the kprobe is hit while a handler is running on the same CPU. Probing the functions that
+//! handle the breakpoints hangs the kernel.
+
+use core::{
+    ops::Range,
+    ptr::null_mut,
+    sync::atomic::{AtomicPtr, AtomicUsize, Ordering},
+};
+
+use x86_64::registers::rflags::RFlags;
+
+use crate::{
+    cpu::all_cpus,
+    cpu_local, cpu_local_cell,
+    mm::{kspace::kernel_loaded_offset, paddr_to_vaddr},
+    prelude::*,
+    sync::Mutex,
+    trap::{self, TrapFrame},
+    Error,
+};
+
+/// The handler of a kprobe.
+pub type KprobeHandler = fn(&TrapFrame);
+
+/// The maximum number of kprobes.
+const MAX_KPROBES: usize = 64;
+
+const INT3: u8 = 0xcc;
+const PUSH_RBP: u8 = 0x55;
+
+struct Kprobe {
+    addr: Vaddr,
+    handler: KprobeHandler,
+}
+
+/// The registered kprobes.
+///
+/// The breakpoint handler looks up the kprobes without locking. A kprobe is freed only after
+/// no breakpoint handler can access it, see [`wait_for_handlers`].
+static KPROBES: [AtomicPtr<Kprobe>; MAX_KPROBES] =
+    [const { AtomicPtr::new(null_mut()) }; MAX_KPROBES];
+
+/// Serializes the registration and the unregistration of the kprobes.
+static REGISTER_LOCK: Mutex<()> = Mutex::new(());
+
+cpu_local! {
+    /// The sequence number of the kprobe handlers, which is odd if and only if a handler is
+    /// running on the CPU.
+    static HANDLER_SEQ: AtomicUsize = AtomicUsize::new(0);
+}
+
+cpu_local_cell! {
+    /// The address where the execution resumes after the replaced instruction is executed
+    /// out of line.
+    static RESUME_ADDR: Vaddr = 0;
+    /// Whether the local IRQs are enabled when the kprobe is hit.
+    static RESUME_IRQ_ENABLED: bool = false;
+}
+
+// Executes the `push rbp` instruction replaced by a kprobe, and traps back to the breakpoint
+// handler, which resumes the execution in the probed function.
+core::arch::global_asm!(
+    ".section .text",
+    ".global __kprobe_push_rbp",
+    "__kprobe_push_rbp:",
+    "    push rbp",
+    "    int3",
+);
+
+extern "C" {
+    fn __stext();
+    fn __etext();
+    fn __kprobe_push_rbp();
+}
+
+/// Registers a kprobe at the start of a kernel function.
+///
+/// This function returns an error if the address is not the start of a function that can be
+/// probed, if the address has been probed, or if there are too many kprobes.
+///
+/// # Panics
+///
+/// This function panics if it is called in atomic mode.
+pub fn register_kprobe(addr: Vaddr, handler: KprobeHandler) -> Result<()> {
+    if !kernel_text().contains(&addr) || addr == __kprobe_push_rbp as usize {
+        return Err(Error::InvalidArgs);
+    }
+
+    let _guard = REGISTER_LOCK.lock();
+
+    if find_kprobe(addr).is_some() {
+        return Err(Error::InvalidArgs);
+    }
+    // SAFETY: The address is in the kernel code, which is mapped.
+    if unsafe { (addr as *const u8).read_volatile() } != PUSH_RBP {
+        return Err(Error::InvalidArgs);
+    }
+    let slot = KPROBES
+        .iter()
+        .find(|slot| slot.load(Ordering::Relaxed).is_null())
+        .ok_or(Error::NotEnoughResources)?;
+
+    let kprobe = Box::new(Kprobe { addr, handler });
+    slot.store(Box::into_raw(kprobe), Ordering::SeqCst);
+    write_kernel_text(addr, INT3);
+
+    Ok(())
+}
+
+/// Unregisters the kprobe at the address.
+///
+/// After this function returns, the handler of the kprobe is not running and will not be
+/// called. This function returns an error if the address is not probed.
+///
+/// # Panics
+///
+/// This function panics if it is called in atomic mode.
+pub fn unregister_kprobe(addr: Vaddr) -> Result<()> {
+    let _guard = REGISTER_LOCK.lock();
+
+    let slot = KPROBES
+        .iter()
+        .find(|slot| {
+            let kprobe = slot.load(Ordering::Relaxed);
+            // SAFETY: The kprobes are freed only with `REGISTER_LOCK` held.
+            !kprobe.is_null() && unsafe { (*kprobe).addr } == addr
+        })
+        .ok_or(Error::InvalidArgs)?;
+
+    // Restore the function before removing the kprobe, so that a missing kprobe means that
+    // the breakpoint has been removed. See `handle_breakpoint`.
+    write_kernel_text(addr, PUSH_RBP);
+    let kprobe = slot.swap(null_mut(), Ordering::SeqCst);
+    wait_for_handlers();
+    // SAFETY: The kprobe was allocated by `register_kprobe`, and no breakpoint handler can
+    // access it after the handlers that may have found it have returned.
+    drop(unsafe { Box::from_raw(kprobe) });
+
+    Ok(())
+}
+
+/// Returns the addresses of the registered kprobes.
+pub fn kprobe_addrs() -> Vec<Vaddr> {
+    let _guard = REGISTER_LOCK.lock();
+
+    KPROBES
+        .iter()
+        .map(|slot| slot.load(Ordering::Relaxed))
+        .filter(|kprobe| !kprobe.is_null())
+        // SAFETY: The kprobes are freed only with `REGISTER_LOCK` held.
+        .map(|kprobe| unsafe { (*kprobe).addr })
+        .collect()
+}
+
+/// Handles a breakpoint in the kernel mode.
+///
+/// This function returns `false` if the breakpoint is not set by a kprobe.
+pub(super) fn handle_breakpoint(f: &mut TrapFrame) -> bool {
+    // The breakpoint is a trap, so the instruction pointer is after the `int3` instruction.
+    let addr = f.rip - 1;
+
+    if addr == __kprobe_push_rbp as usize + 1 {
+        f.rip = RESUME_ADDR.load();
+        if RESUME_IRQ_ENABLED.load() {
+            f.rflags |= RFlags::INTERRUPT_FLAG.bits() as usize;
+        }
+        return true;
+    }
+
+    let irq_guard = trap::disable_local();
+    let handler_seq = HANDLER_SEQ.get_with(&irq_guard);
+    let is_nested = handler_seq.load(Ordering::Relaxed) % 2 == 1;
+    if !is_nested {
+        handler_seq.fetch_add(1, Ordering::SeqCst);
+    }
+    let kprobe = find_kprobe(addr);
+    if let Some(kprobe) = kprobe
+        && !is_nested
+    {
+        f.rip = addr;
+        (kprobe.handler)(f);
+    }
+    let is_probed = kprobe.is_some();
+    if !is_nested {
+        handler_seq.fetch_add(1, Ordering::Release);
+    }
+
+    if is_probed {
+        // Execute the replaced instruction with the local IRQs disabled, so that the resume
+        // address cannot be overwritten by another kprobe on this CPU.
+        RESUME_ADDR.store(addr + 1);
+        RESUME_IRQ_ENABLED.store(f.rflags & RFlags::INTERRUPT_FLAG.bits() as usize != 0);
+        f.rflags &= !(RFlags::INTERRUPT_FLAG.bits() as usize);
+        f.rip = __kprobe_push_rbp as usize;
+        return true;
+    }
+
+    // The kprobe is unregistered after the breakpoint is hit. Execute the restored instruction.
+    if kernel_text().contains(&addr)
+        // SAFETY: The address is in the kernel code, which is mapped.
+        && unsafe { (addr as *const u8).read_volatile() } != INT3
+    {
+        f.rip = addr;
+        return true;
+    }
+
+    false
+}
+
+fn find_kprobe(addr: Vaddr) -> Option<&'static Kprobe> {
+    KPROBES.iter().find_map(|slot| {
+        let kprobe = slot.load(Ordering::SeqCst);
+        // SAFETY: The kprobe is not freed while the caller is running a breakpoint handler or
+        // is holding `REGISTER_LOCK`.
+        let kprobe = unsafe { kprobe.as_ref()? };
+        (kprobe.addr == addr).then_some(kprobe)
+    })
+}
+
+/// Waits until the breakpoint handlers that are running on the CPUs have returned.
+fn wait_for_handlers() {
+    for cpu in all_cpus() {
+        let handler_seq = HANDLER_SEQ.get_on_cpu(cpu);
+        let seq = handler_seq.load(Ordering::SeqCst);
+        if seq % 2 == 0 {
+            continue;
+        }
+        while handler_seq.load(Ordering::Acquire) == seq {
+            core::hint::spin_loop();
+        }
+    }
+}
+
+fn kernel_text() -> Range<Vaddr> {
+    __stext as usize..__etext as usize
+}
+
+/// Writes a byte of the kernel code.
+///
+/// The byte is written through the linear mapping, since the kernel code may not be mapped
+/// as writable. Writing a single byte is atomic to the CPUs that are executing the code.
+fn write_kernel_text(addr: Vaddr, byte: u8) {
+    let alias = paddr_to_vaddr(addr - kernel_loaded_offset());
+    // SAFETY: The address is in the kernel code, and only the first byte of a function, which
+    // is either `push rbp` or `int3`, is written.
+    unsafe { (alias as *mut u8).write_volatile(byte) };
+}
+
+#[cfg(ktest)]
+mod test {
+    use ostd_macros::ktest;
+
+    use super::*;
+
+    static NUM_HITS: AtomicUsize = AtomicUsize::new(0);
+
+    #[inline(never)]
+    fn probed_fn(x: usize) -> usize {
+        core::hint::black_box(x) + 1
+    }
+
+    fn count_hit(f: &TrapFrame) {
+        assert_eq!(f.rdi, 41);
+        NUM_HITS.fetch_add(1, Ordering::Relaxed);
+    }
+
+    #[ktest]
+    fn hit_kprobe() {
+        let addr = probed_fn as usize;
+        register_kprobe(addr, count_hit).unwrap();
+        assert_eq!(register_kprobe(addr, count_hit), Err(Error::InvalidArgs));
+        assert_eq!(probed_fn(41), 42);
+        assert_eq!(kprobe_addrs(), [addr]);
+
+        unregister_kprobe(addr).unwrap();
+        assert_eq!(probed_fn(41), 42);
+        assert_eq!(NUM_HITS.load(Ordering::Relaxed), 1);
+    }
+}
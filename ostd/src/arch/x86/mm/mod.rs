@@ -111,6 +111,11 @@ pub(crate) fn tlb_flush_all_including_global() {
     }
 }
 
+/// Makes the instruction caches of all CPUs coherent with the data caches.
+///
+/// This is a no-op since the instruction caches are always coherent on x86.
+pub(crate) fn sync_icache_all() {}
+
 #[derive(Clone, Copy, Pod, Default)]
 #[repr(C)]
 pub struct PageTableEntry(usize);
@@ -6,11 +6,16 @@ pub mod boot;
 pub(crate) mod cpu;
 pub mod device;
 pub(crate) mod ex_table;
+pub(crate) mod freq;
+pub(crate) mod idle;
 pub mod iommu;
 pub(crate) mod irq;
 pub(crate) mod kernel;
+pub mod kgdb;
+pub mod kprobe;
 pub(crate) mod mm;
 pub(crate) mod pci;
+pub(crate) mod power;
 pub mod qemu;
 pub mod serial;
 pub mod task;
@@ -33,13 +38,17 @@ cfg_if! {
 }
 
 use core::{
-    arch::x86_64::{_rdrand64_step, _rdtsc},
+    arch::x86_64::{_rdrand64_step, _rdseed64_step, _rdtsc},
     sync::atomic::Ordering,
 };
 
+pub use cpu::fsgsbase::has_fsgsbase;
 use kernel::apic::ioapic;
+pub use kernel::kvmclock;
 use log::{info, warn};
 
+use crate::mm::numa::NumaTopology;
+
 #[cfg(feature = "cvm_guest")]
 pub(crate) fn init_cvm_guest() {
     match init_tdx() {
@@ -63,13 +72,42 @@ pub(crate) fn init_cvm_guest() {
 
 static CPU_FEATURES: Once<FeatureInfo> = Once::new();
 
+/// Initializes the firmware tables that describe the platform.
+///
+/// This function is called before the frame allocator is initialized, so that the NUMA
+/// topology can be used to set up the frame allocator.
+pub(crate) fn init_firmware_tables() {
+    kernel::acpi::init();
+}
+
+/// Returns the NUMA topology reported by the ACPI SRAT.
+pub(crate) fn numa_topology() -> Option<NumaTopology> {
+    let srat = kernel::acpi::srat::Srat::new()?;
+
+    let memory_ranges = srat
+        .memory_affinities()
+        .iter()
+        .map(|affinity| (affinity.range.clone(), affinity.proximity_domain))
+        .collect();
+    // The CPU IDs are the same as the local APIC IDs.
+    let cpus = srat
+        .processor_affinities()
+        .iter()
+        .map(|affinity| (affinity.apic_id, affinity.proximity_domain))
+        .collect();
+
+    Some(NumaTopology {
+        memory_ranges,
+        cpus,
+    })
+}
+
 pub(crate) fn init_on_bsp() {
     // SAFETY: this function is only called once on BSP.
     unsafe {
         crate::arch::trap::init(true);
     }
     irq::init();
-    kernel::acpi::init();
 
     // SAFETY: they are only called once on BSP and ACPI has been initialized.
     unsafe {
@@ -87,6 +125,7 @@ pub(crate) fn init_on_bsp() {
         }
     }
     serial::callback_init();
+    power::init();
 
     // SAFETY: no CPU local objects have been accessed by this far. And
     // we are on the BSP.
@@ -155,8 +194,13 @@ pub fn read_random() -> Option<u64> {
     // Software Developer’s Manual" - Volume 1 - Section 7.3.17.1.
     const RETRY_LIMIT: usize = 10;
 
+    if !has_rdrand() {
+        return None;
+    }
+
     for _ in 0..RETRY_LIMIT {
         let mut val = 0;
+        // SAFETY: The `RDRAND` instruction is supported.
         let generated = unsafe { _rdrand64_step(&mut val) };
         if generated == 1 {
             return Some(val);
@@ -165,6 +209,55 @@ pub fn read_random() -> Option<u64> {
     None
 }
 
+/// Reads a hardware generated 64-bit random seed.
+///
+/// Unlike [`read_random`], the value comes directly from the entropy source
+/// (i.e., `RDSEED`), so it is suitable for seeding. Returns None if no random
+/// seed was generated.
+pub fn read_random_seed() -> Option<u64> {
+    // The entropy source may be exhausted temporarily, so more retries are
+    // needed than `RDRAND`. See "Intel® Digital Random Number Generator (DRNG)
+    // Software Implementation Guide" - Section 5.3.1.
+    const RETRY_LIMIT: usize = 100;
+
+    if !has_rdseed() {
+        return None;
+    }
+
+    for _ in 0..RETRY_LIMIT {
+        let mut val = 0;
+        // SAFETY: The `RDSEED` instruction is supported.
+        let generated = unsafe { _rdseed64_step(&mut val) };
+        if generated == 1 {
+            return Some(val);
+        }
+        core::hint::spin_loop();
+    }
+    None
+}
+
+fn has_rdrand() -> bool {
+    use core::arch::x86_64::__cpuid;
+
+    let cpuid_result = unsafe { __cpuid(1) };
+    // Check for RDRAND (bit 30 of ecx)
+    cpuid_result.ecx & (1 << 30) != 0
+}
+
+fn has_rdseed() -> bool {
+    use core::arch::x86_64::{__cpuid, __cpuid_count};
+
+    let cpuid_result = unsafe { __cpuid(0) };
+    if cpuid_result.eax < 7 {
+        // CPUID function 7 is not supported
+        return false;
+    }
+
+    let cpuid_result = unsafe { __cpuid_count(7, 0) };
+    // Check for RDSEED (bit 18 of ebx)
+    cpuid_result.ebx & (1 << 18) != 0
+}
+
 fn has_avx512() -> bool {
     use core::arch::x86_64::{__cpuid, __cpuid_count};
 
@@ -188,13 +281,11 @@ pub(crate) fn enable_cpu_features() {
     });
 
     cpu::enable_essential_features();
+    cpu::fsgsbase::init();
 
     let mut cr4 = x86_64::registers::control::Cr4::read();
-    cr4 |= Cr4Flags::FSGSBASE
-        | Cr4Flags::OSXSAVE
-        | Cr4Flags::OSFXSR
-        | Cr4Flags::OSXMMEXCPT_ENABLE
-        | Cr4Flags::PAGE_GLOBAL;
+    cr4 |=
+        Cr4Flags::OSXSAVE | Cr4Flags::OSFXSR | Cr4Flags::OSXMMEXCPT_ENABLE | Cr4Flags::PAGE_GLOBAL;
     unsafe {
         x86_64::registers::control::Cr4::write(cr4);
     }
@@ -0,0 +1,48 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! Turning off and restarting the x86 machine.
+
+use log::warn;
+
+use super::{
+    device::io_port::{IoPort, WriteOnlyAccess},
+    kernel::acpi,
+};
+
+/// The command port of the i8042 keyboard controller.
+static I8042_COMMAND: IoPort<u8, WriteOnlyAccess> = unsafe { IoPort::new(0x64) };
+/// The i8042 command that pulses the reset line of the CPU.
+const I8042_PULSE_RESET: u8 = 0xfe;
+
+pub(crate) fn init() {
+    acpi::power::init();
+}
+
+pub(crate) fn poweroff() -> ! {
+    super::irq::disable_local();
+
+    acpi::power::enter_s5();
+
+    warn!("Failed to turn off the machine, halting");
+    halt()
+}
+
+pub(crate) fn restart() -> ! {
+    super::irq::disable_local();
+
+    acpi::power::reset();
+
+    // Like Linux, fall back to the keyboard controller, which is available on most machines.
+    I8042_COMMAND.write(I8042_PULSE_RESET);
+
+    warn!("Failed to restart the machine, halting");
+    halt()
+}
+
+pub(crate) fn halt() -> ! {
+    super::irq::disable_local();
+
+    loop {
+        x86_64::instructions::hlt();
+    }
+}
@@ -105,6 +105,9 @@ fn handle_serial_input(trap_frame: &TrapFrame) {
     };
     let received_char = receive_char().unwrap();
     debug!("receive char = {:?}", received_char);
+    if super::kgdb::handle_console_input(received_char) {
+        return;
+    }
     for callback in lock.iter() {
         callback(received_char);
     }
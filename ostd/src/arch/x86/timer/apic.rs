@@ -2,7 +2,6 @@
 
 #![allow(unused_variables)]
 
-use alloc::sync::Arc;
 use core::{
     arch::x86_64::_rdtsc,
     sync::atomic::{AtomicBool, AtomicU64, Ordering},
@@ -41,7 +40,111 @@ pub(super) fn init() -> IrqLine {
     }
 }
 
-pub(super) static APIC_TIMER_CALLBACK: Once<Arc<dyn Fn() + Sync + Send>> = Once::new();
+/// The operating mode of the APIC timers.
+#[derive(Debug, Clone, Copy)]
+enum TimerMode {
+    /// The timers interrupt at the programmed TSC deadlines.
+    TscDeadline,
+    /// The timers interrupt periodically, counting down from the initial count.
+    Periodic { init_count: u64 },
+}
+
+static TIMER_MODE: Once<TimerMode> = Once::new();
+
+/// The TSC deadline of the next system tick, which is only handled by the BSP.
+static NEXT_TICK_TSC: AtomicU64 = AtomicU64::new(0);
+
+/// Initializes the APIC timer of the current AP in the same mode as the BSP.
+///
+/// In the TSC deadline mode, the timer is not armed until a high-resolution timer
+/// is armed on the AP.
+pub(super) fn init_on_ap(irq_num: u8) {
+    match TIMER_MODE.get().unwrap() {
+        TimerMode::TscDeadline => apic::with_borrow(|apic| {
+            apic.set_lvt_timer(irq_num as u64 | (1 << 18));
+        }),
+        TimerMode::Periodic { init_count } => apic::with_borrow(|apic| {
+            apic.set_timer_init_count(*init_count);
+            apic.set_lvt_timer(irq_num as u64 | (1 << 17));
+            apic.set_timer_div_config(DivideConfig::Divide64);
+        }),
+    }
+}
+
+/// The TSC value before which the tick of the BSP is stopped, or zero if the tick
+/// is not stopped.
+static TICK_STOPPED_UNTIL: AtomicU64 = AtomicU64::new(0);
+
+/// Returns the number of system ticks that are due on a timer interrupt.
+///
+/// In the periodic mode, every timer interrupt on the BSP is a tick. In the TSC
+/// deadline mode, the ticks missed because of a late interrupt or a stopped tick
+/// are all counted, and the deadline of the next tick is advanced accordingly.
+pub(super) fn nr_due_ticks(is_bsp: bool) -> u64 {
+    if !is_bsp {
+        return 0;
+    }
+    let Some(TimerMode::TscDeadline) = TIMER_MODE.get() else {
+        return 1;
+    };
+
+    // SAFETY: It is safe to read a time-related counter.
+    let now = unsafe { _rdtsc() };
+    let next_tick = NEXT_TICK_TSC.load(Ordering::Relaxed);
+    if now < next_tick || now < TICK_STOPPED_UNTIL.load(Ordering::Relaxed) {
+        return 0;
+    }
+    let nr_ticks = (now - next_tick) / tsc_step() + 1;
+    NEXT_TICK_TSC.store(next_tick + nr_ticks * tsc_step(), Ordering::Relaxed);
+    nr_ticks
+}
+
+/// Stops the tick of the BSP until `until_ns`.
+///
+/// It only takes effect in the TSC deadline mode.
+pub(super) fn stop_tick(until_ns: u64) {
+    TICK_STOPPED_UNTIL.store(ns_to_tsc(until_ns).max(1), Ordering::Relaxed);
+}
+
+/// Restarts the tick of the BSP.
+pub(super) fn restart_tick() {
+    TICK_STOPPED_UNTIL.store(0, Ordering::Relaxed);
+}
+
+/// Programs the TSC deadline of the current CPU.
+///
+/// The deadline is the earlier one of the next system tick, if on the BSP, and
+/// `next_expiration_ns`. In the periodic mode, the timer is left unchanged.
+pub(super) fn program_next_event(is_bsp: bool, next_expiration_ns: Option<u64>) {
+    let Some(TimerMode::TscDeadline) = TIMER_MODE.get() else {
+        return;
+    };
+
+    let next_tick = if is_bsp {
+        NEXT_TICK_TSC
+            .load(Ordering::Relaxed)
+            .max(TICK_STOPPED_UNTIL.load(Ordering::Relaxed))
+    } else {
+        u64::MAX
+    };
+    let next_expiration = next_expiration_ns.map_or(u64::MAX, ns_to_tsc);
+    // Writing zero disarms the timer.
+    let deadline = match next_tick.min(next_expiration) {
+        u64::MAX => 0,
+        deadline => deadline.max(1),
+    };
+    // SAFETY: Writing the TSC deadline only affects the timer interrupts.
+    unsafe { wrmsr(IA32_TSC_DEADLINE, deadline) };
+}
+
+fn tsc_step() -> u64 {
+    TSC_FREQ.load(Ordering::Relaxed) / TIMER_FREQ
+}
+
+fn ns_to_tsc(ns: u64) -> u64 {
+    let tsc = ns as u128 * TSC_FREQ.load(Ordering::Relaxed) as u128 / 1_000_000_000;
+    tsc.try_into().unwrap_or(u64::MAX)
+}
 
 /// Determines if the current system supports tsc_deadline mode APIC timer
 fn is_tsc_deadline_mode_supported() -> bool {
@@ -56,16 +159,12 @@ fn init_tsc_mode() -> IrqLine {
     apic::with_borrow(|apic| {
         apic.set_lvt_timer(timer_irq.num() as u64 | (1 << 18));
     });
-    let tsc_step = TSC_FREQ.load(Ordering::Relaxed) / TIMER_FREQ;
-
-    let callback = move || unsafe {
-        let tsc_value = _rdtsc();
-        let next_tsc_value = tsc_step + tsc_value;
-        wrmsr(IA32_TSC_DEADLINE, next_tsc_value);
-    };
+    TIMER_MODE.call_once(|| TimerMode::TscDeadline);
 
-    callback.call(());
-    APIC_TIMER_CALLBACK.call_once(|| Arc::new(callback));
+    // SAFETY: It is safe to read a time-related counter.
+    let next_tick = unsafe { _rdtsc() } + tsc_step();
+    NEXT_TICK_TSC.store(next_tick, Ordering::Relaxed);
+    program_next_event(true, None);
 
     timer_irq
 }
@@ -97,9 +196,11 @@ fn init_periodic_mode() -> IrqLine {
 
     // Init APIC Timer
     let timer_irq = IrqLine::alloc().unwrap();
+    let init_count = INIT_COUNT.load(Ordering::Relaxed);
+    TIMER_MODE.call_once(|| TimerMode::Periodic { init_count });
 
     apic::with_borrow(|apic| {
-        apic.set_timer_init_count(INIT_COUNT.load(Ordering::Relaxed));
+        apic.set_timer_init_count(init_count);
         apic.set_lvt_timer(timer_irq.num() as u64 | (1 << 17));
         apic.set_timer_div_config(DivideConfig::Divide64);
     });
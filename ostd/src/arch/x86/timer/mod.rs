@@ -10,11 +10,11 @@ use core::sync::atomic::Ordering;
 
 use spin::Once;
 
-use self::apic::APIC_TIMER_CALLBACK;
 use crate::{
     arch::x86::kernel,
-    timer::INTERRUPT_CALLBACKS,
-    trap::{self, IrqLine, TrapFrame},
+    cpu::{CpuId, PinCurrentCpu},
+    timer::{hrtimer, INTERRUPT_CALLBACKS},
+    trap::{self, DisabledLocalIrqGuard, IrqLine, TrapFrame},
 };
 
 /// The timer frequency (Hz).
@@ -51,17 +51,69 @@ pub(super) fn init() {
     TIMER_IRQ.call_once(|| timer_irq);
 }
 
-fn timer_callback(_: &TrapFrame) {
-    crate::timer::jiffies::ELAPSED.fetch_add(1, Ordering::SeqCst);
+/// Initializes the timer of the current AP.
+///
+/// This function waits for the BSP to initialize its timer.
+pub(crate) fn init_on_ap() {
+    let irq_num = TIMER_IRQ.wait().num();
+    if kernel::apic::exists() {
+        apic::init_on_ap(irq_num);
+    }
+}
 
-    let irq_guard = trap::disable_local();
-    let callbacks_guard = INTERRUPT_CALLBACKS.get_with(&irq_guard);
-    for callback in callbacks_guard.borrow().iter() {
-        (callback)();
+/// Programs the timer of the current CPU for the earliest expiration of the
+/// high-resolution timers on the CPU.
+pub(crate) fn program_next_event(guard: &DisabledLocalIrqGuard) {
+    if kernel::apic::exists() {
+        let is_bsp = guard.current_cpu() == CpuId::bsp();
+        apic::program_next_event(is_bsp, hrtimer::next_expiration(guard));
+    }
+}
+
+/// Stops the tick of the current CPU until `until_ns`.
+///
+/// Only the BSP has a tick, and it can only be stopped in the TSC deadline mode.
+pub(crate) fn stop_tick(guard: &DisabledLocalIrqGuard, until_ns: u64) {
+    if kernel::apic::exists() && guard.current_cpu() == CpuId::bsp() {
+        apic::stop_tick(until_ns);
+        program_next_event(guard);
+    }
+}
+
+/// Restarts the tick of the current CPU.
+///
+/// The ticks missed while the tick is stopped are accounted on the next timer
+/// interrupt, which arrives at once if any tick is missed.
+pub(crate) fn restart_tick(guard: &DisabledLocalIrqGuard) {
+    if kernel::apic::exists() && guard.current_cpu() == CpuId::bsp() {
+        apic::restart_tick();
+        program_next_event(guard);
     }
-    drop(callbacks_guard);
+}
+
+fn timer_callback(trap_frame: &TrapFrame) {
+    let irq_guard = trap::disable_local();
+    let is_bsp = irq_guard.current_cpu() == CpuId::bsp();
 
-    if APIC_TIMER_CALLBACK.is_completed() {
-        APIC_TIMER_CALLBACK.get().unwrap().call(());
+    let nr_ticks = if kernel::apic::exists() {
+        apic::nr_due_ticks(is_bsp)
+    } else {
+        1
+    };
+    if nr_ticks > 0 {
+        crate::timer::jiffies::ELAPSED.fetch_add(nr_ticks, Ordering::SeqCst);
+
+        let callbacks_guard = INTERRUPT_CALLBACKS.get_with(&irq_guard);
+        for callback in callbacks_guard.borrow().iter() {
+            (callback)();
+        }
+        drop(callbacks_guard);
+
+        crate::timer::call_sample_callback(trap_frame);
+        crate::task::watchdog::check(trap_frame);
+        crate::sync::rcu::pass_quiescent_state_on_tick();
     }
+
+    hrtimer::expire_timers(&irq_guard);
+    program_next_event(&irq_guard);
 }
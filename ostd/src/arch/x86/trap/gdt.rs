@@ -10,6 +10,8 @@
 // We make the following new changes:
 // * Link TaskStateSegment to .cpu_local area.
 // * Init TaskStateSegment on bsp/ap respectively.
+// * Add the per-CPU CPUNODE segment for `getcpu` in the vDSO.
+// * Add the thread-local storage segments of the 32-bit compatibility mode.
 //
 // These changes are released under the following license:
 //
@@ -17,15 +19,15 @@
 
 //! Configure Global Descriptor Table (GDT).
 
-use alloc::{boxed::Box, vec::Vec};
+use alloc::{boxed::Box, vec, vec::Vec};
 use core::cell::SyncUnsafeCell;
 
 use x86_64::{
-    instructions::tables::{lgdt, load_tss, sgdt},
-    registers::{
-        model_specific::Star,
-        segmentation::{Segment64, GS},
+    instructions::{
+        segmentation::{Segment, DS, ES},
+        tables::{lgdt, load_tss, sgdt},
     },
+    registers::model_specific::Star,
     structures::{
         gdt::{Descriptor, SegmentSelector},
         tss::TaskStateSegment,
@@ -34,6 +36,11 @@ use x86_64::{
     PrivilegeLevel, VirtAddr,
 };
 
+use crate::{
+    arch::cpu::tls::{GDT_ENTRY_TLS_MIN, NR_TLS_SEGMENTS},
+    cpu::CpuId,
+};
+
 /// Init TSS & GDT.
 pub unsafe fn init(on_bsp: bool) {
     // Allocate stack for trap from user, set the stack top to TSS,
@@ -62,6 +69,19 @@ pub unsafe fn init(on_bsp: bool) {
     //   STAR[63:48] = U_CS32 = U_SS32 - 8 = U_CS - 16
     let mut gdt = Vec::from(old_gdt);
     gdt.extend([tss0, tss1, KCODE64, KDATA64, UCODE32, UDATA32, UCODE64].iter());
+
+    // Reserve the thread-local storage segments and add the CPUNODE segment at the indexes
+    // fixed by Linux.
+    let cpu_id = if on_bsp {
+        CpuId::bsp()
+    } else {
+        let irq_guard = crate::trap::disable_local();
+        crate::cpu::try_current_cpu(&irq_guard).unwrap()
+    };
+    assert!(gdt.len() <= GDT_ENTRY_TLS_MIN);
+    gdt.resize(GDT_ENTRY_TLS_MIN + NR_TLS_SEGMENTS, 0);
+    assert_eq!(gdt.len(), GDT_ENTRY_CPUNODE);
+    gdt.push(cpunode_segment(cpu_id));
     let gdt = Vec::leak(gdt);
 
     // Load new GDT and TSS.
@@ -78,10 +98,71 @@ pub unsafe fn init(on_bsp: bool) {
     let syscall = SegmentSelector::new(entry_count as u16 + 2, PrivilegeLevel::Ring0).0;
     Star::write_raw(sysret, syscall);
 
+    USER_CS32 = sysret;
     USER_SS = sysret + 8;
     USER_CS = sysret + 16;
 }
 
+/// The index of the CPUNODE segment in the GDT, which is the same as Linux.
+///
+/// The vDSO routine `getcpu` loads the segment limit with the `lsl` instruction to get the
+/// number of the current CPU and the NUMA node without system calls.
+pub(in crate::arch) const GDT_ENTRY_CPUNODE: usize = 15;
+
+/// Returns the CPUNODE segment of the CPU.
+///
+/// It is a user data segment, whose limit is `(node << 12) | cpu` like Linux. Since NUMA is
+/// not supported, the node is always zero.
+fn cpunode_segment(cpu_id: CpuId) -> u64 {
+    CPUNODE_SEGMENT | (cpu_id.as_usize() as u64 & 0xfff)
+}
+
+/// Loads the thread-local storage segments into the GDT of the current CPU.
+///
+/// The local IRQs must be disabled, so that the current task is not migrated to another CPU.
+pub(in crate::arch) fn load_tls_descriptors(descriptors: &[u64; NR_TLS_SEGMENTS]) {
+    let gdt = sgdt().base.as_mut_ptr::<u64>();
+    for (index, descriptor) in descriptors.iter().enumerate() {
+        // SAFETY: The GDT of the current CPU has the thread-local storage entries (see `init`)
+        // and is only used by the current CPU. The entries describe the user data segments,
+        // which are never used by the kernel.
+        unsafe {
+            gdt.add(GDT_ENTRY_TLS_MIN + index)
+                .write_volatile(*descriptor)
+        };
+    }
+}
+
+/// Loads the data segment selectors for the 32-bit compatibility mode on the current CPU.
+///
+/// The data segments are ignored in the 64-bit mode, but the null data segments cannot be used
+/// in the compatibility mode. Like Linux, the kernel keeps the user data segments loaded.
+pub(in crate::arch) fn load_user_data_selectors() {
+    // SAFETY: `USER_SS` is only written in `init`.
+    let selector = SegmentSelector(unsafe { USER_SS });
+    if DS::get_reg() == selector && ES::get_reg() == selector {
+        return;
+    }
+
+    // SAFETY: The selector refers to the user data segment, which is present. The data segments
+    // are not used by the kernel in the 64-bit mode.
+    unsafe {
+        DS::set_reg(selector);
+        ES::set_reg(selector);
+    }
+}
+
+/// The index of the interrupt stack table entry for double faults.
+///
+/// Double faults are handled on a dedicated stack, since they are usually
+/// caused by kernel stack overflows, after which the current stack is unusable.
+pub(super) const DOUBLE_FAULT_IST_INDEX: u16 = 0;
+
+/// The size of the stack for double faults.
+///
+/// It is large enough to print the diagnostics and the backtrace.
+const DOUBLE_FAULT_STACK_SIZE: usize = 0x10000;
+
 // The linker script ensure that cpu_local_tss section is right
 // at the beginning of cpu_local area, so that gsbase (offset zero)
 // points to LOCAL_TSS.
@@ -94,18 +175,30 @@ unsafe fn init_local_tss_on_bsp() -> &'static TaskStateSegment {
 
     let trap_stack_top = Box::leak(Box::new([0u8; 0x1000])).as_ptr() as u64 + 0x1000;
     (*tss_ptr).privilege_stack_table[0] = VirtAddr::new(trap_stack_top);
+    (*tss_ptr).interrupt_stack_table[DOUBLE_FAULT_IST_INDEX as usize] = alloc_double_fault_stack();
     &*tss_ptr
 }
 
 unsafe fn init_local_tss_on_ap() -> &'static TaskStateSegment {
-    let gs_base = GS::read_base().as_u64();
+    let gs_base = crate::arch::cpu::local::get_base();
     let tss_ptr = gs_base as *mut TaskStateSegment;
 
     let trap_stack_top = Box::leak(Box::new([0u8; 0x1000])).as_ptr() as u64 + 0x1000;
     (*tss_ptr).privilege_stack_table[0] = VirtAddr::new(trap_stack_top);
+    (*tss_ptr).interrupt_stack_table[DOUBLE_FAULT_IST_INDEX as usize] = alloc_double_fault_stack();
     &*tss_ptr
 }
 
+/// Allocates a stack for double faults and returns its top.
+fn alloc_double_fault_stack() -> VirtAddr {
+    let stack = vec![0u8; DOUBLE_FAULT_STACK_SIZE].leak();
+    let stack_top = stack.as_ptr() as u64 + DOUBLE_FAULT_STACK_SIZE as u64;
+    // The stack pointer should be aligned to 16 bytes.
+    VirtAddr::new(stack_top & !0xf)
+}
+
+#[no_mangle]
+static mut USER_CS32: u16 = 0;
 #[no_mangle]
 static mut USER_SS: u16 = 0;
 #[no_mangle]
@@ -118,3 +211,4 @@ const KDATA64: u64 = 0x00009200_00000000; // DATA_WRITABLE | USER_SEGMENT | PRES
 const UDATA64: u64 = 0x0000F200_00000000; // DATA_WRITABLE | USER_SEGMENT | USER_MODE | PRESENT
 const UCODE32: u64 = 0x00cffa00_0000ffff; // EXECUTABLE | USER_SEGMENT | USER_MODE | PRESENT
 const UDATA32: u64 = 0x00cff200_0000ffff; // EXECUTABLE | USER_SEGMENT | USER_MODE | PRESENT
+const CPUNODE_SEGMENT: u64 = 0x0040F500_00000000; // USER_SEGMENT | USER_MODE | PRESENT | DEFAULT_SIZE
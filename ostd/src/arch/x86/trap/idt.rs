@@ -10,6 +10,7 @@
 // We make the following new changes:
 // * Include `trap.S` in this file and remove unused function `sidt`.
 // * Link `VECTORS` to `trap_handler_table` defined in `trap.S`.
+// * Enable user space `int 0x80` for 32-bit system calls.
 //
 // These changes are released under the following license:
 //
@@ -42,6 +43,15 @@ pub fn init() {
         if i == 3 || i == 4 {
             opt.set_privilege_level(PrivilegeLevel::Ring3);
         }
+        // Enable user space `int 0x80`, which issues 32-bit system calls.
+        if i == super::COMPAT_SYSCALL_VECTOR as usize {
+            opt.set_privilege_level(PrivilegeLevel::Ring3);
+        }
+        // Handle double faults on a dedicated stack.
+        if i == 8 {
+            // SAFETY: The stack is set in the TSS of each CPU and is used only by double faults.
+            unsafe { opt.set_stack_index(super::gdt::DOUBLE_FAULT_IST_INDEX) };
+        }
     }
     idt.load();
 }
@@ -20,8 +20,11 @@ mod gdt;
 mod idt;
 mod syscall;
 
+use core::{mem::size_of, ops::Range};
+
 use align_ext::AlignExt;
 use cfg_if::cfg_if;
+pub(super) use gdt::{load_tls_descriptors, load_user_data_selectors, GDT_ENTRY_CPUNODE};
 use log::debug;
 
 use super::ex_table::ExTable;
@@ -31,9 +34,9 @@ use crate::{
     mm::{
         kspace::{KERNEL_PAGE_TABLE, LINEAR_MAPPING_BASE_VADDR, LINEAR_MAPPING_VADDR_RANGE},
         page_prop::{CachePolicy, PageProperty},
-        PageFlags, PrivilegedPageFlags as PrivFlags, MAX_USERSPACE_VADDR, PAGE_SIZE,
+        PageFlags, PrivilegedPageFlags as PrivFlags, Vaddr, MAX_USERSPACE_VADDR, PAGE_SIZE,
     },
-    task::Task,
+    task::{current_kernel_stack, find_overflowed_stack, Task},
     trap::call_irq_callback_functions,
 };
 
@@ -124,6 +127,9 @@ pub unsafe fn init(on_bsp: bool) {
     syscall::init();
 }
 
+/// The interrupt vector of `int 0x80`, which issues 32-bit system calls like Linux.
+pub(crate) const COMPAT_SYSCALL_VECTOR: u8 = 0x80;
+
 /// User space context.
 #[derive(Debug, Default, Clone, Copy, Eq, PartialEq)]
 #[repr(C)]
@@ -132,6 +138,9 @@ pub struct UserContext {
     pub general: GeneralRegs,
     pub trap_num: usize,
     pub error_code: usize,
+    /// Whether the user space runs in the 32-bit compatibility mode (non-zero) or in the
+    /// 64-bit mode (zero).
+    pub compat_mode: usize,
 }
 
 /// General registers.
@@ -217,6 +226,56 @@ pub fn is_kernel_interrupted() -> bool {
     KERNEL_INTERRUPT_NESTED_LEVEL.load() != 0
 }
 
+/// Walks the call chain of the kernel code that is interrupted with the trap frame.
+///
+/// The visitor is called with the interrupted instruction pointer first, and then
+/// with the return address of each caller, until it returns `false` or the chain
+/// ends. The callers are found by following the frame pointers, so the call chain
+/// is complete only if the kernel is built with frame pointers. The frame pointers
+/// are followed only within the kernel stack of the current task.
+pub fn walk_kernel_stack(f: &TrapFrame, mut visit: impl FnMut(Vaddr) -> bool) {
+    if !visit(f.rip) {
+        return;
+    }
+    let Some(stack) = current_kernel_stack() else {
+        return;
+    };
+    walk_frame_pointers(f.rbp, stack, visit);
+}
+
+/// Walks the call chain by following the frame pointers from `frame_pointer`.
+///
+/// The visitor is called with the return address of each caller, until it returns
+/// `false` or the chain ends. Only the frames within `stack` are followed.
+pub(crate) fn walk_frame_pointers(
+    mut frame_pointer: usize,
+    stack: Range<Vaddr>,
+    mut visit: impl FnMut(Vaddr) -> bool,
+) {
+    // Each frame pointer points to the caller's frame pointer, which is followed
+    // by the return address.
+    while frame_pointer % size_of::<usize>() == 0
+        && stack.start <= frame_pointer
+        && frame_pointer + 2 * size_of::<usize>() <= stack.end
+    {
+        // SAFETY: The two words are within the kernel stack, which is mapped. The stack
+        // may be modified concurrently if it is not the interrupted one, so the words
+        // are read as volatile and are only used as hints.
+        let (caller_frame_pointer, return_address) = unsafe {
+            let frame = frame_pointer as *const usize;
+            (frame.read_volatile(), frame.add(1).read_volatile())
+        };
+        if return_address == 0 || !visit(return_address) {
+            return;
+        }
+        // The stack grows downwards, so the frames of the callers are at higher addresses.
+        if caller_frame_pointer <= frame_pointer {
+            return;
+        }
+        frame_pointer = caller_frame_pointer;
+    }
+}
+
 /// Handle traps (only from kernel).
 #[no_mangle]
 extern "sysv64" fn trap_handler(f: &mut TrapFrame) {
@@ -228,6 +287,10 @@ extern "sysv64" fn trap_handler(f: &mut TrapFrame) {
             handle_virtual_exception(&mut trapframe_wrapper, &ve_info);
             *f = *trapframe_wrapper.0;
         }
+        Some(CpuException::BREAKPOINT)
+            if super::kprobe::handle_breakpoint(f) || super::kgdb::handle_breakpoint(f) => {}
+        Some(CpuException::DEBUG) if super::kgdb::handle_debug(f) => {}
+        Some(CpuException::DOUBLE_FAULT) => handle_double_fault(f),
         Some(CpuException::PAGE_FAULT) => {
             let page_fault_addr = x86_64::registers::control::Cr2::read_raw();
             // The actual user space implementation should be responsible
@@ -280,6 +343,24 @@ fn handle_user_page_fault(f: &mut TrapFrame, page_fault_addr: u64) {
     }
 }
 
+/// Handles double faults, which are usually caused by kernel stack overflows.
+///
+/// The handler runs on a dedicated stack, so it can still report the overflow.
+fn handle_double_fault(f: &TrapFrame) -> ! {
+    // SAFETY: The CPU always pushes the stack pointer of the interrupted context
+    // right after `rflags`, which is the last field of the trap frame.
+    let rsp = unsafe { *(&f.rflags as *const usize).add(1) };
+
+    if let Some(stack) = find_overflowed_stack(rsp) {
+        panic!(
+            "kernel stack overflow: the stack pointer {:#x} is out of the kernel stack {:#x?} \
+             of the current task; trapframe: {:#x?}",
+            rsp, stack, f
+        );
+    }
+    panic!("cannot handle kernel double fault, trapframe: {:#x?}", f);
+}
+
 /// FIXME: this is a hack because we don't allocate kernel space for IO memory. We are currently
 /// using the linear mapping for IO memory. This is not a good practice.
 fn handle_kernel_page_fault(f: &TrapFrame, page_fault_vaddr: u64) {
@@ -289,6 +370,14 @@ fn handle_kernel_page_fault(f: &TrapFrame, page_fault_vaddr: u64) {
         page_fault_vaddr as *const (), error_code
     );
 
+    if let Some(stack) = find_overflowed_stack(page_fault_vaddr as usize) {
+        panic!(
+            "kernel stack overflow: the address {:#x} is out of the kernel stack {:#x?} \
+             of the current task; trapframe: {:#x?}",
+            page_fault_vaddr, stack, f
+        );
+    }
+
     assert!(
         LINEAR_MAPPING_VADDR_RANGE.contains(&(page_fault_vaddr as usize)),
         "kernel page fault: the address is outside the range of the linear mapping",
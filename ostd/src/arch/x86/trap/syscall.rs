@@ -21,7 +21,6 @@ use core::arch::global_asm;
 use x86::cpuid::CpuId;
 use x86_64::{
     registers::{
-        control::{Cr4, Cr4Flags},
         model_specific::{Efer, EferFlags, LStar, SFMask},
         rflags::RFlags,
     },
@@ -44,12 +43,6 @@ pub fn init() {
             efer.insert(EferFlags::SYSTEM_CALL_EXTENSIONS);
         });
 
-        // Enable `FSGSBASE` instructions.
-        assert!(cpuid.get_extended_feature_info().unwrap().has_fsgsbase());
-        Cr4::update(|cr4| {
-            cr4.insert(Cr4Flags::FSGSBASE);
-        });
-
         // Flags to clear on syscall.
         // Copy from Linux 5.0, TF|DF|IF|IOPL|AC|NT
         const RFLAGS_MASK: u64 = 0x47700;
@@ -147,6 +147,8 @@ fn ap_early_entry(local_apic_id: u32) -> ! {
         .is_started
         .store(true, Ordering::Release);
 
+    crate::arch::timer::init_on_ap();
+
     log::info!("Processor {} started. Spinning for tasks.", local_apic_id);
 
     let ap_late_entry = AP_LATE_ENTRY.wait();
@@ -46,6 +46,64 @@ pub(crate) fn init() {
     // FIXME: The address 0xFEB0_0000 is obtained from an instance of microvm, and it may not work in other architecture.
     #[cfg(target_arch = "x86_64")]
     iter_range(0xFEB0_0000..0xFEB0_4000);
+    #[cfg(any(target_arch = "riscv64", target_arch = "aarch64"))]
+    iter_device_tree();
+}
+
+/// Registers the virtio-mmio devices in the device tree, whose interrupts are routed
+/// through the PLIC on RISC-V and the GIC on AArch64.
+#[cfg(any(target_arch = "riscv64", target_arch = "aarch64"))]
+fn iter_device_tree() {
+    use crate::arch::boot::DEVICE_TREE;
+    #[cfg(target_arch = "aarch64")]
+    use crate::arch::gic::{parse_interrupt, GIC as INTERRUPT_CONTROLLER};
+    #[cfg(target_arch = "riscv64")]
+    use crate::arch::plic::PLIC as INTERRUPT_CONTROLLER;
+
+    let Some(interrupt_controller) = INTERRUPT_CONTROLLER.get() else {
+        return;
+    };
+    let mut lock = MMIO_BUS.lock();
+    for node in DEVICE_TREE.get().unwrap().all_nodes() {
+        if !node
+            .compatible()
+            .is_some_and(|compatible| compatible.all().any(|c| c == "virtio,mmio"))
+        {
+            continue;
+        }
+        let Some(region) = node.reg().and_then(|mut reg| reg.next()) else {
+            continue;
+        };
+        #[cfg(target_arch = "riscv64")]
+        let source = node
+            .interrupts()
+            .and_then(|mut interrupts| interrupts.next())
+            .map(|source| source as u32);
+        #[cfg(target_arch = "aarch64")]
+        let source = node
+            .property("interrupts")
+            .and_then(|interrupts| parse_interrupt(interrupts.value, 0));
+        let Some(source) = source else {
+            continue;
+        };
+
+        let paddr = region.starting_address as usize;
+        // SAFETY: It only read the value and judge if the magic value fit 0x74726976
+        let magic = unsafe { core::ptr::read_volatile(paddr_to_vaddr(paddr) as *const u32) };
+        // SAFETY: It only read the device id
+        let device_id =
+            unsafe { core::ptr::read_volatile(paddr_to_vaddr(paddr + 8) as *const u32) };
+        // QEMU creates the transports for all the slots, and the empty ones have a
+        // device ID of zero.
+        if magic != VIRTIO_MMIO_MAGIC || device_id == 0 {
+            continue;
+        }
+
+        let handle = IrqLine::alloc().unwrap();
+        interrupt_controller.enable(source, handle.clone()).unwrap();
+        let device = MmioCommonDevice::new(paddr, handle);
+        lock.register_mmio_device(device);
+    }
 }
 
 #[cfg(target_arch = "x86_64")]
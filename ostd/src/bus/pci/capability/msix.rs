@@ -16,8 +16,10 @@ use crate::{
         common_device::PciCommonDevice,
         device_info::PciDeviceLocation,
     },
+    cpu::CpuId,
     mm::VmIoOnce,
     trap::IrqLine,
+    Error, Result,
 };
 
 cfg_if! {
@@ -171,6 +173,9 @@ impl CapabilityMsixData {
         if index >= self.table_size {
             return;
         }
+        if let Some(old_irq) = self.irqs[index as usize].as_ref() {
+            old_irq.inner_irq().set_retarget_fn(None);
+        }
 
         // If interrupt remapping is enabled, then we need to change the value of the message address.
         if has_interrupt_remapping() {
@@ -179,6 +184,7 @@ impl CapabilityMsixData {
             // Enable irt entry
             let irt_entry_mut = handle.irt_entry_mut().unwrap();
             irt_entry_mut.enable_default(irq.num() as u32);
+            irt_entry_mut.set_destination_id(irq.inner_irq().affinity().as_usize() as u32);
 
             // Use remappable format. The bits[4:3] should be always set to 1 according to the manual.
             let mut address = MSIX_DEFAULT_MSG_ADDR | 0b1_1000;
@@ -196,6 +202,15 @@ impl CapabilityMsixData {
                 .write_once((16 * index + 8) as usize + self.table_offset, &0)
                 .unwrap();
         } else {
+            let table_bar = self.table_bar.clone();
+            let entry_offset = (16 * index) as usize + self.table_offset;
+            irq.inner_irq()
+                .set_retarget_fn(Some(Arc::new(move |cpu_id| {
+                    set_message_destination(&table_bar, entry_offset, cpu_id)
+                })));
+            set_message_destination(&self.table_bar, entry_offset, irq.inner_irq().affinity())
+                .unwrap();
+
             self.table_bar
                 .io_mem()
                 .write_once(
@@ -225,6 +240,35 @@ impl CapabilityMsixData {
     }
 }
 
+/// Sets the CPU that an MSI-X table entry delivers its message to.
+///
+/// Without interrupt remapping, the destination is the 8-bit APIC ID in bits 19:12 of the
+/// message address.
+fn set_message_destination(
+    table_bar: &MemoryBar,
+    entry_offset: usize,
+    cpu_id: CpuId,
+) -> Result<()> {
+    let apic_id = u8::try_from(cpu_id.as_usize()).map_err(|_| Error::InvalidArgs)?;
+    let address = MSIX_DEFAULT_MSG_ADDR | (apic_id as u32) << 12;
+
+    // The entry is masked while the address is modified, as required by the PCI spec.
+    let vector_control: u32 = table_bar.io_mem().read_once(entry_offset + 12).unwrap();
+    table_bar
+        .io_mem()
+        .write_once(entry_offset + 12, &(vector_control | 1))
+        .unwrap();
+    table_bar
+        .io_mem()
+        .write_once(entry_offset, &address)
+        .unwrap();
+    table_bar
+        .io_mem()
+        .write_once(entry_offset + 12, &vector_control)
+        .unwrap();
+    Ok(())
+}
+
 fn set_bit(origin_value: u16, offset: usize, set: bool) -> u16 {
     (origin_value & (!(1 << offset))) | ((set as u16) << offset)
 }
@@ -0,0 +1,269 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! CPU frequency information and scaling.
+//!
+//! The frequencies of the CPUs are reported by the platform, e.g., by the
+//! CPUID leaf 0x16 or the timing leaf of the hypervisors on x86. The effective
+//! frequency of a CPU, which differs from the base frequency when the CPU is
+//! boosted or throttled, is measured with the platform counters if they are
+//! available, e.g., `APERF` and `MPERF` on x86.
+//!
+//! The effective frequency and the utilization of a CPU are sampled when the
+//! CPU becomes idle, at most once every [`SAMPLE_INTERVAL_NS`]. Each sample is
+//! passed to the current [`FreqGovernor`], which chooses the target frequency
+//! of the CPU. The target frequency takes effect only if the platform supports
+//! frequency scaling, which is usually not the case in virtual machines.
+
+use alloc::vec;
+use core::sync::atomic::{AtomicU64, Ordering};
+
+use spin::Once;
+
+use super::{CpuId, PinCurrentCpu};
+use crate::{
+    arch, cpu_local,
+    prelude::*,
+    sync::{LocalIrqDisabled, SpinLock},
+    timer::monotonic_ns,
+    trap::DisabledLocalIrqGuard,
+    Error,
+};
+
+/// The minimum interval between two samples of a CPU, in nanoseconds.
+pub const SAMPLE_INTERVAL_NS: u64 = 10_000_000;
+
+/// The frequencies of the CPUs, in kHz.
+#[derive(Debug, Clone, Copy)]
+pub struct FreqInfo {
+    /// The minimum frequency.
+    pub min_khz: u64,
+    /// The base (nominal) frequency.
+    pub base_khz: u64,
+    /// The maximum frequency, including the boosted frequencies.
+    pub max_khz: u64,
+}
+
+/// A governor that chooses the target frequencies of the CPUs.
+pub trait FreqGovernor: Sync + Send {
+    /// Returns the name of the governor.
+    fn name(&self) -> &'static str;
+
+    /// Returns the target frequency of the CPU in kHz, given the percentage of
+    /// the time that the CPU has been busy since the last sample.
+    ///
+    /// The returned frequency is clamped to the range of [`FreqInfo`].
+    fn target_khz(&self, info: &FreqInfo, cpu_id: CpuId, util_percent: u64) -> u64;
+}
+
+/// The governor that always runs the CPUs at the maximum frequency.
+#[derive(Debug)]
+pub struct PerformanceGovernor;
+
+impl FreqGovernor for PerformanceGovernor {
+    fn name(&self) -> &'static str {
+        "performance"
+    }
+
+    fn target_khz(&self, info: &FreqInfo, _cpu_id: CpuId, _util_percent: u64) -> u64 {
+        info.max_khz
+    }
+}
+
+/// The governor that always runs the CPUs at the minimum frequency.
+#[derive(Debug)]
+pub struct PowersaveGovernor;
+
+impl FreqGovernor for PowersaveGovernor {
+    fn name(&self) -> &'static str {
+        "powersave"
+    }
+
+    fn target_khz(&self, info: &FreqInfo, _cpu_id: CpuId, _util_percent: u64) -> u64 {
+        info.min_khz
+    }
+}
+
+/// The governor that scales the frequency of a CPU with its utilization.
+///
+/// Like the `schedutil` governor of Linux, the target frequency is 1.25 times
+/// the maximum frequency scaled by the utilization, so that a CPU that is
+/// busier than 80% runs at the maximum frequency.
+#[derive(Debug)]
+pub struct OndemandGovernor;
+
+impl FreqGovernor for OndemandGovernor {
+    fn name(&self) -> &'static str {
+        "ondemand"
+    }
+
+    fn target_khz(&self, info: &FreqInfo, _cpu_id: CpuId, util_percent: u64) -> u64 {
+        info.max_khz * util_percent * 5 / 4 / 100
+    }
+}
+
+static INFO: Once<FreqInfo> = Once::new();
+
+static GOVERNOR: SpinLock<&'static dyn FreqGovernor, LocalIrqDisabled> =
+    SpinLock::new(&PerformanceGovernor);
+
+static REGISTERED_GOVERNORS: SpinLock<Vec<&'static dyn FreqGovernor>, LocalIrqDisabled> =
+    SpinLock::new(Vec::new());
+
+cpu_local! {
+    /// The effective frequency of the CPU in the last sample, in kHz.
+    static CUR_KHZ: AtomicU64 = AtomicU64::new(0);
+    /// The time of the last sample.
+    static LAST_SAMPLE_NS: AtomicU64 = AtomicU64::new(0);
+    /// The total idle time of the CPU at the last sample.
+    static LAST_IDLE_NS: AtomicU64 = AtomicU64::new(0);
+}
+
+pub(crate) fn init() {
+    INFO.call_once(arch::freq::probe);
+}
+
+/// Returns the frequencies of the CPUs.
+pub fn info() -> FreqInfo {
+    *INFO.get().unwrap()
+}
+
+/// Returns the effective frequency of the CPU in kHz.
+///
+/// If the effective frequency cannot be measured, the base frequency is
+/// returned.
+pub fn cur_freq_khz(cpu_id: CpuId) -> u64 {
+    match CUR_KHZ.get_on_cpu(cpu_id).load(Ordering::Relaxed) {
+        0 => info().base_khz,
+        khz => khz,
+    }
+}
+
+/// Returns whether the frequencies of the CPUs can be changed.
+pub fn can_scale() -> bool {
+    arch::freq::can_scale()
+}
+
+/// Returns the name of the platform driver that changes the frequencies, if
+/// any.
+pub fn driver_name() -> Option<&'static str> {
+    arch::freq::driver_name()
+}
+
+/// Registers a governor, which can then be selected with [`set_governor`].
+pub fn register_governor(governor: &'static dyn FreqGovernor) {
+    REGISTERED_GOVERNORS.lock().push(governor);
+}
+
+/// Returns the available governors.
+pub fn available_governors() -> Vec<&'static dyn FreqGovernor> {
+    let mut governors: Vec<&'static dyn FreqGovernor> =
+        vec![&PerformanceGovernor, &PowersaveGovernor, &OndemandGovernor];
+    governors.extend(REGISTERED_GOVERNORS.lock().iter());
+    governors
+}
+
+/// Returns the current governor.
+pub fn governor() -> &'static dyn FreqGovernor {
+    *GOVERNOR.lock()
+}
+
+/// Selects the governor with the given name for all the CPUs.
+///
+/// The new governor takes effect on the next sample of each CPU.
+pub fn set_governor(name: &str) -> Result<()> {
+    let governor = available_governors()
+        .into_iter()
+        .find(|governor| governor.name() == name)
+        .ok_or(Error::InvalidArgs)?;
+    *GOVERNOR.lock() = governor;
+    Ok(())
+}
+
+/// Samples the effective frequency and the utilization of the current CPU,
+/// and applies the target frequency chosen by the governor.
+///
+/// This function should be called when the current CPU becomes idle.
+pub(crate) fn sample(guard: &DisabledLocalIrqGuard) {
+    let Some(info) = INFO.get() else {
+        return;
+    };
+
+    let now_ns = monotonic_ns();
+    let last_sample_ns = LAST_SAMPLE_NS.get_with(guard);
+    let elapsed_ns = now_ns.saturating_sub(last_sample_ns.load(Ordering::Relaxed));
+    if elapsed_ns < SAMPLE_INTERVAL_NS {
+        return;
+    }
+    last_sample_ns.store(now_ns, Ordering::Relaxed);
+
+    let cpu_id = guard.current_cpu();
+    let idle_ns = super::idle::total_idle_ns(cpu_id);
+    let last_idle_ns = LAST_IDLE_NS
+        .get_with(guard)
+        .swap(idle_ns, Ordering::Relaxed);
+    let busy_ns = elapsed_ns.saturating_sub(idle_ns.saturating_sub(last_idle_ns));
+    let util_percent = busy_ns * 100 / elapsed_ns;
+
+    if let Some(khz) = arch::freq::measure_khz(guard, info) {
+        CUR_KHZ.get_with(guard).store(khz, Ordering::Relaxed);
+    }
+
+    if can_scale() {
+        let target_khz = governor()
+            .target_khz(info, cpu_id, util_percent)
+            .clamp(info.min_khz, info.max_khz);
+        arch::freq::set_target_khz(guard, target_khz);
+    }
+}
+
+#[cfg(ktest)]
+mod test {
+    use ostd_macros::ktest;
+
+    use super::*;
+
+    /// A governor that runs the CPUs at the base frequency.
+    struct BaseGovernor;
+
+    impl FreqGovernor for BaseGovernor {
+        fn name(&self) -> &'static str {
+            "base"
+        }
+
+        fn target_khz(&self, info: &FreqInfo, _cpu_id: CpuId, _util_percent: u64) -> u64 {
+            info.base_khz
+        }
+    }
+
+    #[ktest]
+    fn switch_governor() {
+        let info = FreqInfo {
+            min_khz: 800_000,
+            base_khz: 2_000_000,
+            max_khz: 3_200_000,
+        };
+        let target_khz = |util_percent| governor().target_khz(&info, CpuId::bsp(), util_percent);
+        let old_governor = governor().name();
+
+        set_governor("powersave").unwrap();
+        assert_eq!(governor().name(), "powersave");
+        assert_eq!(target_khz(100), info.min_khz);
+
+        set_governor("ondemand").unwrap();
+        assert_eq!(target_khz(40), info.max_khz / 2);
+        assert!(target_khz(90) >= info.max_khz);
+
+        // An unknown governor is rejected without changing the current one.
+        assert!(matches!(set_governor("base"), Err(Error::InvalidArgs)));
+        assert_eq!(governor().name(), "ondemand");
+
+        register_governor(&BaseGovernor);
+        assert!(available_governors()
+            .iter()
+            .any(|governor| governor.name() == "base"));
+        set_governor("base").unwrap();
+        assert_eq!(target_khz(100), info.base_khz);
+
+        set_governor(old_governor).unwrap();
+    }
+}
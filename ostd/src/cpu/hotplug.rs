@@ -0,0 +1,181 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! CPU hotplug.
+//!
+//! An application processor (AP) can be taken offline at runtime and brought
+//! online again later. Offlining a CPU works as follows:
+//!
+//! 1. The CPU is removed from the [`online_cpus`], so that the scheduler no
+//!    longer puts tasks onto it, and the tasks in its runqueue are moved to
+//!    the online CPUs by [`Scheduler::offline_cpu`]. The IRQs delivered to
+//!    the CPU are delivered to the BSP instead.
+//! 2. The CPU is notified to preempt its current task. Once it has nothing
+//!    else to run, its idle task calls [`idle_current_cpu`], which runs the
+//!    offline callbacks and then parks the CPU.
+//! 3. A parked CPU halts until it is brought online again. It still handles
+//!    interrupts, e.g., the IPIs and its local timer, but runs no tasks.
+//!
+//! The per-CPU data of an offline CPU are torn down by the offline callbacks
+//! registered with [`register_offline_callback`], which are called on the
+//! offline CPU. The free heap blocks cached by the CPU are given back as well.
+//! When the CPU is brought online again, the online callbacks registered with
+//! [`register_online_callback`] are called on it to set up the per-CPU data,
+//! before it runs any tasks.
+//!
+//! The bootstrap processor (BSP) cannot go offline, since it keeps the system
+//! time and handles the external interrupts by default.
+//!
+//! [`Scheduler::offline_cpu`]: crate::task::scheduler::Scheduler::offline_cpu
+//! [`idle_current_cpu`]: crate::task::scheduler::idle_current_cpu
+
+use core::sync::atomic::{AtomicU8, Ordering};
+
+use spin::Once;
+
+use super::{AtomicCpuSet, CpuId, CpuSet, PinCurrentCpu};
+use crate::{
+    cpu_local,
+    prelude::*,
+    sync::{Mutex, RwLock},
+    task::{scheduler, Task},
+    trap::{self, DisabledLocalIrqGuard},
+    Error,
+};
+
+/// The CPU is online.
+const ONLINE: u8 = 0;
+/// The CPU is requested to go offline, but has not been parked.
+const GOING_OFFLINE: u8 = 1;
+/// The CPU is parked.
+const OFFLINE: u8 = 2;
+
+cpu_local! {
+    static STATE: AtomicU8 = AtomicU8::new(ONLINE);
+}
+
+static ONLINE_CPUS: Once<AtomicCpuSet> = Once::new();
+
+/// Serializes the onlining and offlining of the CPUs.
+static HOTPLUG_LOCK: Mutex<()> = Mutex::new(());
+
+static OFFLINE_CALLBACKS: RwLock<Vec<fn(CpuId)>> = RwLock::new(Vec::new());
+static ONLINE_CALLBACKS: RwLock<Vec<fn(CpuId)>> = RwLock::new(Vec::new());
+
+pub(crate) fn init() {
+    ONLINE_CPUS.call_once(|| AtomicCpuSet::new(CpuSet::new_full()));
+}
+
+/// Returns the set of the online CPUs.
+pub fn online_cpus() -> CpuSet {
+    ONLINE_CPUS.get().unwrap().load()
+}
+
+/// Returns whether the CPU is online.
+pub fn is_online(cpu_id: CpuId) -> bool {
+    ONLINE_CPUS
+        .get()
+        .unwrap()
+        .contains(cpu_id, Ordering::Relaxed)
+}
+
+/// Registers a callback that is called on a CPU right before it goes offline.
+///
+/// The callback is called with local IRQs disabled, and should release the
+/// per-CPU resources of the CPU.
+pub fn register_offline_callback(callback: fn(CpuId)) {
+    OFFLINE_CALLBACKS.write().push(callback);
+}
+
+/// Registers a callback that is called on a CPU right after it is brought
+/// online again.
+///
+/// The callback is called with local IRQs disabled, before the CPU runs any
+/// tasks. It is not called on the CPUs that are online since booting.
+pub fn register_online_callback(callback: fn(CpuId)) {
+    ONLINE_CALLBACKS.write().push(callback);
+}
+
+/// Takes a CPU offline.
+///
+/// This function returns after the CPU is parked. It returns an error if the
+/// CPU is the BSP or is already offline.
+///
+/// # Panics
+///
+/// This function panics if it is called in atomic mode.
+pub fn offline_cpu(cpu_id: CpuId) -> Result<()> {
+    let _hotplug_guard = HOTPLUG_LOCK.lock();
+
+    if cpu_id == CpuId::bsp() || !is_online(cpu_id) {
+        return Err(Error::InvalidArgs);
+    }
+
+    STATE
+        .get_on_cpu(cpu_id)
+        .store(GOING_OFFLINE, Ordering::Release);
+    ONLINE_CPUS.get().unwrap().remove(cpu_id, Ordering::Release);
+
+    scheduler::offline_cpu(cpu_id);
+    trap::migrate_irqs_away_from(cpu_id);
+    scheduler::set_need_preempt(cpu_id);
+
+    while STATE.get_on_cpu(cpu_id).load(Ordering::Acquire) != OFFLINE {
+        Task::yield_now();
+    }
+    log::info!("CPU {} is offline", cpu_id.as_usize());
+
+    Ok(())
+}
+
+/// Brings an offline CPU online.
+///
+/// This function returns an error if the CPU is already online.
+///
+/// # Panics
+///
+/// This function panics if it is called in atomic mode.
+pub fn online_cpu(cpu_id: CpuId) -> Result<()> {
+    let _hotplug_guard = HOTPLUG_LOCK.lock();
+
+    if is_online(cpu_id) {
+        return Err(Error::InvalidArgs);
+    }
+
+    STATE.get_on_cpu(cpu_id).store(ONLINE, Ordering::Release);
+    ONLINE_CPUS.get().unwrap().add(cpu_id, Ordering::Release);
+
+    // The interrupt brings the CPU out of the halted state.
+    scheduler::set_need_preempt(cpu_id);
+    log::info!("CPU {} is online", cpu_id.as_usize());
+
+    Ok(())
+}
+
+/// Parks the current CPU if it is requested to go offline.
+///
+/// This function is called by the idle task of the current CPU. It returns `true`
+/// after the current CPU is brought online again, or `false` immediately if the
+/// CPU should stay online.
+pub(crate) fn park_current_cpu_if_offline(irq_guard: &DisabledLocalIrqGuard) -> bool {
+    let cpu_id = irq_guard.current_cpu();
+    let state = STATE.get_with(irq_guard);
+    if state.load(Ordering::Acquire) != GOING_OFFLINE {
+        return false;
+    }
+
+    for callback in OFFLINE_CALLBACKS.read().iter() {
+        callback(cpu_id);
+    }
+    crate::mm::heap_allocator::drain_cpu_cache(irq_guard);
+
+    state.store(OFFLINE, Ordering::Release);
+    while state.load(Ordering::Acquire) == OFFLINE {
+        crate::arch::irq::enable_local_and_halt();
+        crate::arch::irq::disable_local();
+    }
+
+    for callback in ONLINE_CALLBACKS.read().iter() {
+        callback(cpu_id);
+    }
+    true
+}
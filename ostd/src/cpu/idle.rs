@@ -0,0 +1,293 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! CPU idle states and halt-polling.
+//!
+//! When a CPU has nothing to run, it first polls for new work for a short
+//! while before halting. Waking up a halted CPU is expensive in a virtual
+//! machine, since the host has to schedule the virtual CPU again, so polling
+//! reduces the wakeup latency if new work arrives soon. Like the haltpoll
+//! governor of Linux, the polling time of each CPU adapts to how long the CPU
+//! stays idle: it grows if the CPU is woken up shortly after halting, and
+//! shrinks if the CPU stays idle for longer than [`max_poll_ns`].
+//!
+//! If no work arrives while polling, the CPU enters the deepest idle state
+//! whose target residency does not exceed the time until the next timer event.
+//! Deeper states save more power but take longer to exit. The available states
+//! are platform-specific, e.g., `HLT` and the `MWAIT` C-states on x86.
+
+use core::sync::atomic::{AtomicU64, Ordering};
+
+use super::{CpuId, PinCurrentCpu};
+use crate::{arch, cpu_local, timer::monotonic_ns, trap::DisabledLocalIrqGuard};
+
+/// The maximum number of idle states.
+pub(crate) const MAX_IDLE_STATES: usize = 8;
+
+/// The default maximum polling time in virtual machines, which is the same as Linux.
+const DEFAULT_MAX_POLL_NS: u64 = 200_000;
+/// The polling time that a CPU starts with when its polling time grows from zero.
+const POLL_GROW_START_NS: u64 = 50_000;
+const POLL_GROW_FACTOR: u64 = 2;
+const POLL_SHRINK_FACTOR: u64 = 2;
+
+static MAX_POLL_NS: AtomicU64 = AtomicU64::new(0);
+
+cpu_local! {
+    /// The current polling time of the CPU.
+    static POLL_LIMIT_NS: AtomicU64 = AtomicU64::new(0);
+    static USAGE: [UsageCounters; MAX_IDLE_STATES] =
+        [const { UsageCounters::new() }; MAX_IDLE_STATES];
+}
+
+/// An idle state of the CPUs.
+#[derive(Debug)]
+pub struct IdleState {
+    name: &'static str,
+    desc: &'static str,
+    exit_latency_ns: u64,
+    target_residency_ns: u64,
+    /// The platform-specific hint to enter the state.
+    pub(crate) hint: u32,
+}
+
+impl IdleState {
+    pub(crate) const fn new(
+        name: &'static str,
+        desc: &'static str,
+        exit_latency_ns: u64,
+        target_residency_ns: u64,
+        hint: u32,
+    ) -> Self {
+        Self {
+            name,
+            desc,
+            exit_latency_ns,
+            target_residency_ns,
+            hint,
+        }
+    }
+
+    /// Returns the name of the state, e.g., `C1`.
+    pub fn name(&self) -> &'static str {
+        self.name
+    }
+
+    /// Returns the description of the state.
+    pub fn desc(&self) -> &'static str {
+        self.desc
+    }
+
+    /// Returns the time that it takes to exit the state, in nanoseconds.
+    pub fn exit_latency_ns(&self) -> u64 {
+        self.exit_latency_ns
+    }
+
+    /// Returns the minimum time that the CPU should stay in the state to save
+    /// power, in nanoseconds.
+    pub fn target_residency_ns(&self) -> u64 {
+        self.target_residency_ns
+    }
+}
+
+/// The statistics of an idle state on a CPU.
+#[derive(Debug, Clone, Copy)]
+pub struct IdleStateUsage {
+    /// The number of times that the CPU has entered the state.
+    pub usage: u64,
+    /// The total time that the CPU has stayed in the state, in nanoseconds.
+    pub time_ns: u64,
+}
+
+struct UsageCounters {
+    usage: AtomicU64,
+    time_ns: AtomicU64,
+}
+
+impl UsageCounters {
+    const fn new() -> Self {
+        Self {
+            usage: AtomicU64::new(0),
+            time_ns: AtomicU64::new(0),
+        }
+    }
+}
+
+/// Returns the idle states of the CPUs, from the shallowest to the deepest.
+pub fn states() -> &'static [IdleState] {
+    arch::idle::states()
+}
+
+/// Returns the statistics of the idle state at `index` on the CPU.
+///
+/// # Panics
+///
+/// Panics if the index is out of the range of [`states`].
+pub fn usage(cpu_id: CpuId, index: usize) -> IdleStateUsage {
+    assert!(index < states().len());
+
+    let counters = &USAGE.get_on_cpu(cpu_id)[index];
+    IdleStateUsage {
+        usage: counters.usage.load(Ordering::Relaxed),
+        time_ns: counters.time_ns.load(Ordering::Relaxed),
+    }
+}
+
+/// Returns the total time that the CPU has stayed in the idle states, in
+/// nanoseconds.
+pub(crate) fn total_idle_ns(cpu_id: CpuId) -> u64 {
+    USAGE.get_on_cpu(cpu_id)[..states().len()]
+        .iter()
+        .map(|counters| counters.time_ns.load(Ordering::Relaxed))
+        .sum()
+}
+
+/// Returns the maximum time for which an idle CPU polls for new work before
+/// halting, in nanoseconds.
+///
+/// Polling is disabled if it is zero, which is the default on bare metal.
+pub fn max_poll_ns() -> u64 {
+    MAX_POLL_NS.load(Ordering::Relaxed)
+}
+
+/// Sets the maximum time for which an idle CPU polls for new work before
+/// halting, in nanoseconds.
+pub fn set_max_poll_ns(max_poll_ns: u64) {
+    MAX_POLL_NS.store(max_poll_ns, Ordering::Relaxed);
+}
+
+pub(crate) fn init() {
+    arch::idle::init();
+
+    if arch::idle::is_virtualized() {
+        set_max_poll_ns(DEFAULT_MAX_POLL_NS);
+    }
+}
+
+/// Polls until `has_work` returns true or the polling time of the current CPU
+/// runs out.
+///
+/// Local IRQs should be enabled, so that the new work can arrive via
+/// interrupts. Returns whether there is new work.
+pub(crate) fn poll(guard: &dyn PinCurrentCpu, has_work: impl Fn() -> bool) -> bool {
+    let limit_ns = POLL_LIMIT_NS
+        .get_on_cpu(guard.current_cpu())
+        .load(Ordering::Relaxed)
+        .min(max_poll_ns());
+    if limit_ns == 0 {
+        return false;
+    }
+
+    let start_ns = monotonic_ns();
+    loop {
+        if has_work() {
+            return true;
+        }
+        if monotonic_ns().saturating_sub(start_ns) >= limit_ns {
+            return false;
+        }
+        core::hint::spin_loop();
+    }
+}
+
+/// Enters an idle state of the current CPU until an interrupt arrives.
+///
+/// The state is chosen by the time until `wakeup_ns`, when the CPU is expected
+/// to be woken up by the timers. Local IRQs are enabled while the CPU idles,
+/// and they are disabled again when this function returns.
+pub(crate) fn enter(guard: &DisabledLocalIrqGuard, wakeup_ns: u64) {
+    let states = states();
+    let start_ns = monotonic_ns();
+    let expected_ns = wakeup_ns.saturating_sub(start_ns);
+    let index = states
+        .iter()
+        .rposition(|state| state.target_residency_ns <= expected_ns)
+        .unwrap_or(0);
+
+    arch::idle::enter_state(guard, &states[index]);
+    arch::irq::disable_local();
+
+    let counters = &USAGE.get_with(guard)[index];
+    counters.usage.fetch_add(1, Ordering::Relaxed);
+    counters
+        .time_ns
+        .fetch_add(monotonic_ns().saturating_sub(start_ns), Ordering::Relaxed);
+}
+
+/// Adjusts the polling time of the current CPU after it has halted and stayed
+/// idle for `idle_ns`, including the time for polling.
+pub(crate) fn adjust_poll_limit(guard: &DisabledLocalIrqGuard, idle_ns: u64) {
+    let max_poll_ns = max_poll_ns();
+    let poll_limit_ns = POLL_LIMIT_NS.get_with(guard);
+    let old_limit_ns = poll_limit_ns.load(Ordering::Relaxed);
+
+    let new_limit_ns = if idle_ns > old_limit_ns && idle_ns <= max_poll_ns {
+        // The new work arrived shortly after halting, which could have been
+        // caught by polling for a longer time.
+        (old_limit_ns * POLL_GROW_FACTOR)
+            .max(POLL_GROW_START_NS)
+            .min(max_poll_ns)
+    } else if idle_ns > max_poll_ns {
+        // Polling for a long idle period only wastes the CPU time.
+        old_limit_ns / POLL_SHRINK_FACTOR
+    } else {
+        old_limit_ns
+    };
+    poll_limit_ns.store(new_limit_ns, Ordering::Relaxed);
+}
+
+#[cfg(ktest)]
+mod test {
+    use core::cell::Cell;
+
+    use ostd_macros::ktest;
+
+    use super::*;
+    use crate::trap::disable_local;
+
+    #[ktest]
+    fn adaptive_halt_polling() {
+        let old_max_poll_ns = max_poll_ns();
+        set_max_poll_ns(DEFAULT_MAX_POLL_NS);
+
+        let irq_guard = disable_local();
+        let poll_limit_ns = POLL_LIMIT_NS.get_with(&irq_guard);
+        let old_limit_ns = poll_limit_ns.swap(0, Ordering::Relaxed);
+
+        // Polling is disabled until the CPU is woken up shortly after halting.
+        assert!(!poll(&irq_guard, || true));
+
+        // Short idle periods grow the polling time up to the maximum.
+        adjust_poll_limit(&irq_guard, 1_000);
+        assert_eq!(poll_limit_ns.load(Ordering::Relaxed), POLL_GROW_START_NS);
+        adjust_poll_limit(&irq_guard, POLL_GROW_START_NS + 1);
+        assert_eq!(
+            poll_limit_ns.load(Ordering::Relaxed),
+            POLL_GROW_START_NS * POLL_GROW_FACTOR
+        );
+        adjust_poll_limit(&irq_guard, DEFAULT_MAX_POLL_NS);
+        adjust_poll_limit(&irq_guard, DEFAULT_MAX_POLL_NS);
+        assert_eq!(poll_limit_ns.load(Ordering::Relaxed), DEFAULT_MAX_POLL_NS);
+
+        // The work that arrives while polling is caught.
+        let nr_checks = Cell::new(0);
+        assert!(poll(&irq_guard, || {
+            nr_checks.set(nr_checks.get() + 1);
+            nr_checks.get() == 10
+        }));
+
+        // Polling stops once the polling time runs out.
+        let start_ns = monotonic_ns();
+        assert!(!poll(&irq_guard, || false));
+        assert!(monotonic_ns() - start_ns >= DEFAULT_MAX_POLL_NS);
+
+        // Long idle periods shrink the polling time.
+        adjust_poll_limit(&irq_guard, DEFAULT_MAX_POLL_NS + 1);
+        assert_eq!(
+            poll_limit_ns.load(Ordering::Relaxed),
+            DEFAULT_MAX_POLL_NS / POLL_SHRINK_FACTOR
+        );
+
+        poll_limit_ns.store(old_limit_ns, Ordering::Relaxed);
+        set_max_poll_ns(old_max_poll_ns);
+    }
+}
@@ -3,7 +3,8 @@
 //! CPU local storage.
 //!
 //! This module provides a mechanism to define CPU-local objects, by the macro
-//! [`crate::cpu_local!`].
+//! [`crate::cpu_local!`]. The CPU-local objects that cannot be constantly
+//! initialized can be allocated at runtime with [`PerCpu`].
 //!
 //! Such a mechanism exploits the fact that constant values of non-[`Copy`]
 //! types can be bitwise copied. For example, a [`Option<T>`] object, though
@@ -31,6 +32,7 @@
 
 mod cell;
 mod cpu_local;
+mod per_cpu;
 
 pub(crate) mod single_instr;
 
@@ -39,6 +41,7 @@ use alloc::vec::Vec;
 use align_ext::AlignExt;
 pub use cell::CpuLocalCell;
 pub use cpu_local::{CpuLocal, CpuLocalDerefGuard};
+pub use per_cpu::{PerCpu, PerCpuGuard};
 use spin::Once;
 
 use crate::{
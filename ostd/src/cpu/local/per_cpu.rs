@@ -0,0 +1,235 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! Dynamically allocated CPU-local objects.
+
+use alloc::boxed::Box;
+use core::ops::Deref;
+
+use spin::Once;
+
+use crate::{
+    cpu::{all_cpus, CpuId, PinCurrentCpu},
+    task::{disable_preempt, DisabledPreemptGuard},
+    trap::DisabledLocalIrqGuard,
+};
+
+/// An object that has one instance per CPU, which is allocated at runtime.
+///
+/// Unlike the CPU-local objects defined by [`crate::cpu_local!`], whose
+/// initial values must be constant, the instances of a `PerCpu` object are
+/// allocated on the heap and initialized by a closure once the number of CPUs
+/// is known. Each instance is aligned to the cache line to avoid false
+/// sharing.
+///
+/// A `PerCpu` object can be created at runtime with [`PerCpu::new`], or be
+/// declared as a static with [`PerCpu::uninit`] and then initialized with
+/// [`PerCpu::init`].
+///
+/// The instances of all the CPUs, including those that are offline, stay
+/// alive until the object is dropped. To set up or tear down the per-CPU data
+/// when a CPU goes online or offline, register the callbacks with
+/// [`crate::cpu::hotplug`].
+///
+/// # Example
+///
+/// ```rust
+/// use core::sync::atomic::{AtomicUsize, Ordering};
+///
+/// use ostd::cpu::local::PerCpu;
+///
+/// static NR_EVENTS: PerCpu<AtomicUsize> = PerCpu::uninit();
+///
+/// fn init() {
+///     NR_EVENTS.init(|_| AtomicUsize::new(0));
+/// }
+///
+/// fn on_event() {
+///     NR_EVENTS.get().fetch_add(1, Ordering::Relaxed);
+/// }
+///
+/// fn nr_events() -> usize {
+///     NR_EVENTS
+///         .iter()
+///         .map(|(_, nr)| nr.load(Ordering::Relaxed))
+///         .sum()
+/// }
+/// ```
+pub struct PerCpu<T> {
+    slots: Once<Box<[Slot<T>]>>,
+}
+
+/// The instance of a CPU, which is aligned to the cache line.
+#[repr(align(64))]
+struct Slot<T>(T);
+
+impl<T> PerCpu<T> {
+    /// Creates a `PerCpu` object whose instance on each CPU is `init(cpu)`.
+    pub fn new(init: impl FnMut(CpuId) -> T) -> Self {
+        let per_cpu = Self::uninit();
+        per_cpu.init(init);
+        per_cpu
+    }
+
+    /// Creates an uninitialized `PerCpu` object.
+    ///
+    /// The object must be initialized with [`PerCpu::init`] before it is
+    /// accessed.
+    pub const fn uninit() -> Self {
+        Self { slots: Once::new() }
+    }
+
+    /// Initializes the object, whose instance on each CPU is `init(cpu)`.
+    ///
+    /// # Panics
+    ///
+    /// This method panics if the object is already initialized.
+    pub fn init(&self, mut init: impl FnMut(CpuId) -> T) {
+        let mut is_initialized = true;
+        self.slots.call_once(|| {
+            is_initialized = false;
+            all_cpus().map(|cpu| Slot(init(cpu))).collect()
+        });
+        assert!(!is_initialized, "the per-CPU object is already initialized");
+    }
+
+    /// Gets the instance on the current CPU with a provided IRQ guard.
+    ///
+    /// By this method, you can borrow the instance even if `T` is not `Sync`.
+    /// Because that it is per-CPU and IRQs are disabled, no other running
+    /// tasks can access it.
+    ///
+    /// # Panics
+    ///
+    /// This method panics if the object is not initialized.
+    pub fn get_with<'a>(&'a self, guard: &'a DisabledLocalIrqGuard) -> &'a T {
+        &self.slots()[guard.current_cpu().as_usize()].0
+    }
+
+    fn slots(&self) -> &[Slot<T>] {
+        self.slots
+            .get()
+            .expect("the per-CPU object is not initialized")
+    }
+}
+
+impl<T: Sync> PerCpu<T> {
+    /// Gets the instance on the current CPU.
+    ///
+    /// Preemption is disabled while the returned guard is alive, so that the
+    /// instance stays the one of the current CPU.
+    ///
+    /// # Panics
+    ///
+    /// This method panics if the object is not initialized.
+    pub fn get(&self) -> PerCpuGuard<'_, T> {
+        let preempt_guard = disable_preempt();
+        let inner = &self.slots()[preempt_guard.current_cpu().as_usize()].0;
+        PerCpuGuard {
+            inner,
+            _preempt_guard: preempt_guard,
+        }
+    }
+
+    /// Gets the instance on a specific CPU.
+    ///
+    /// # Panics
+    ///
+    /// This method panics if the object is not initialized.
+    pub fn get_on_cpu(&self, cpu_id: CpuId) -> &T {
+        &self.slots()[cpu_id.as_usize()].0
+    }
+
+    /// Iterates over the instances of all CPUs.
+    ///
+    /// # Panics
+    ///
+    /// This method panics if the object is not initialized.
+    pub fn iter(&self) -> impl Iterator<Item = (CpuId, &T)> + '_ {
+        all_cpus().zip(self.slots().iter().map(|slot| &slot.0))
+    }
+}
+
+// SAFETY: The instances can only be shared across CPUs if `T` is `Sync`. If
+// `T` is not `Sync`, an instance can only be accessed on its own CPU with IRQs
+// disabled. The instances may be dropped on any CPU, so `T` must be `Send`.
+unsafe impl<T: Send> Sync for PerCpu<T> {}
+
+/// A guard for accessing the instance of a [`PerCpu`] object on the current
+/// CPU.
+///
+/// It is created by [`PerCpu::get`].
+#[must_use]
+pub struct PerCpuGuard<'a, T> {
+    inner: &'a T,
+    _preempt_guard: DisabledPreemptGuard,
+}
+
+impl<T> Deref for PerCpuGuard<'_, T> {
+    type Target = T;
+
+    fn deref(&self) -> &Self::Target {
+        self.inner
+    }
+}
+
+#[cfg(ktest)]
+mod test {
+    use core::{
+        cell::Cell,
+        sync::atomic::{AtomicUsize, Ordering},
+    };
+
+    use ostd_macros::ktest;
+
+    use super::*;
+    use crate::cpu::num_cpus;
+
+    #[ktest]
+    fn per_cpu_runtime() {
+        let per_cpu = PerCpu::new(|cpu| AtomicUsize::new(cpu.as_usize()));
+        for (cpu, val) in per_cpu.iter() {
+            assert_eq!(val.load(Ordering::Relaxed), cpu.as_usize());
+        }
+
+        let preempt_guard = disable_preempt();
+        let cpu = preempt_guard.current_cpu();
+        per_cpu.get().fetch_add(num_cpus(), Ordering::Relaxed);
+        assert_eq!(
+            per_cpu.get_on_cpu(cpu).load(Ordering::Relaxed),
+            cpu.as_usize() + num_cpus()
+        );
+    }
+
+    #[ktest]
+    fn per_cpu_sum() {
+        static NR_EVENTS: PerCpu<AtomicUsize> = PerCpu::uninit();
+        NR_EVENTS.init(|_| AtomicUsize::new(0));
+
+        // Record `cpu + 1` events on each CPU, and one more on the current CPU.
+        for cpu in all_cpus() {
+            NR_EVENTS
+                .get_on_cpu(cpu)
+                .fetch_add(cpu.as_usize() + 1, Ordering::Relaxed);
+        }
+        NR_EVENTS.get().fetch_add(1, Ordering::Relaxed);
+
+        let nr_events: usize = NR_EVENTS
+            .iter()
+            .map(|(_, nr)| nr.load(Ordering::Relaxed))
+            .sum();
+        assert_eq!(nr_events, num_cpus() * (num_cpus() + 1) / 2 + 1);
+        assert_eq!(NR_EVENTS.iter().count(), num_cpus());
+    }
+
+    #[ktest]
+    fn per_cpu_static() {
+        static FOO: PerCpu<Cell<usize>> = PerCpu::uninit();
+        FOO.init(|_| Cell::new(1));
+
+        let irq_guard = crate::trap::disable_local();
+        let foo = FOO.get_with(&irq_guard);
+        assert_eq!(foo.get(), 1);
+        foo.set(2);
+        assert_eq!(FOO.get_with(&irq_guard).get(), 2);
+    }
+}
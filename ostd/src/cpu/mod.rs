@@ -2,6 +2,9 @@
 
 //! CPU-related definitions.
 
+pub mod freq;
+pub mod hotplug;
+pub mod idle;
 pub mod local;
 pub mod set;
 
@@ -10,6 +13,8 @@ cfg_if::cfg_if! {
         pub use crate::arch::x86::cpu::*;
     } else if #[cfg(target_arch = "riscv64")] {
         pub use crate::arch::riscv::cpu::*;
+    } else if #[cfg(target_arch = "aarch64")] {
+        pub use crate::arch::aarch64::cpu::*;
     }
 }
 
@@ -122,6 +127,13 @@ unsafe impl PinCurrentCpu for DisabledLocalIrqGuard {}
 // to another CPU.
 unsafe impl PinCurrentCpu for DisabledPreemptGuard {}
 
+/// Returns the number of the current CPU, or `None` if the current CPU has not been
+/// initialized.
+pub(crate) fn try_current_cpu(_guard: &dyn PinCurrentCpu) -> Option<CpuId> {
+    let id = CURRENT_CPU.load();
+    (id != u32::MAX).then_some(CpuId(id))
+}
+
 cpu_local_cell! {
     /// The number of the current CPU.
     static CURRENT_CPU: u32 = u32::MAX;
@@ -40,6 +40,7 @@ pub mod io_mem;
 pub mod logger;
 pub mod mm;
 pub mod panic;
+pub mod power;
 pub mod prelude;
 pub mod smp;
 pub mod sync;
@@ -85,23 +86,40 @@ unsafe fn init() {
 
     boot::init_after_heap();
 
+    arch::init_firmware_tables();
+    mm::numa::init();
     mm::frame::allocator::init();
     mm::kspace::init_kernel_page_table(mm::init_page_meta());
     mm::dma::init();
+    cpu::idle::init();
 
     arch::init_on_bsp();
+    // The frequencies may rely on the TSC frequency calibrated with the timer.
+    cpu::freq::init();
+
+    mm::heap_allocator::enable_cpu_caches();
 
     smp::init();
+    cpu::hotplug::init();
 
     // SAFETY: This function is called only once on the BSP.
     unsafe {
         mm::kspace::activate_kernel_page_table();
     }
+    #[cfg(feature = "kasan")]
+    mm::kasan::init();
+    panic::pstore::init();
 
     bus::init();
 
+    #[cfg(feature = "lockdep")]
+    sync::lockdep::init();
+
     arch::irq::enable_local();
 
+    #[cfg(target_arch = "x86_64")]
+    arch::kgdb::init();
+
     invoke_ffi_init_funcs();
 
     IN_BOOTSTRAP_CONTEXT.store(false, Ordering::Relaxed);
@@ -5,14 +5,22 @@
 //! This module provides a default log implementation while allowing users to inject
 //! their own logger at a higher level.
 //!
+//! The recent log messages are also kept in a ring buffer regardless of the logger, so
+//! that they can be included in the report of a panic.
+//!
 //! Generally IRQs are disabled while printing. So do not print long log messages.
 
-use core::str::FromStr;
+use core::{fmt::Write, str::FromStr};
 
 use log::{LevelFilter, Metadata, Record};
 use spin::Once;
 
-use crate::boot::EARLY_INFO;
+use crate::{
+    boot::EARLY_INFO,
+    cpu_local_cell,
+    sync::{LocalIrqDisabled, SpinLock},
+    timer::Jiffies,
+};
 
 static LOGGER: Logger = Logger::new();
 
@@ -51,6 +59,8 @@ impl log::Log for Logger {
     }
 
     fn log(&self, record: &Record) {
+        record_in_ring(record);
+
         if let Some(logger) = self.backend.get() {
             return logger.log(record);
         };
@@ -74,6 +84,90 @@ impl log::Log for Logger {
     }
 }
 
+/// The size of the ring buffer of the recent log messages.
+const LOG_RING_SIZE: usize = 16 * 1024;
+
+static LOG_RING: SpinLock<LogRing, LocalIrqDisabled> = SpinLock::new(LogRing::new());
+
+cpu_local_cell! {
+    /// Whether a message is being recorded in the ring buffer on the CPU.
+    static IS_RECORDING: bool = false;
+}
+
+/// A ring buffer of text, where the new bytes overwrite the oldest ones.
+struct LogRing {
+    buf: [u8; LOG_RING_SIZE],
+    /// The number of bytes ever written.
+    num_written: usize,
+}
+
+impl LogRing {
+    const fn new() -> Self {
+        Self {
+            buf: [0; LOG_RING_SIZE],
+            num_written: 0,
+        }
+    }
+
+    /// Returns the bytes in the buffer in two slices, from the oldest to the newest.
+    fn as_slices(&self) -> (&[u8], &[u8]) {
+        let pos = self.num_written % LOG_RING_SIZE;
+        if self.num_written < LOG_RING_SIZE {
+            (&self.buf[..pos], &[])
+        } else {
+            (&self.buf[pos..], &self.buf[..pos])
+        }
+    }
+}
+
+impl Write for LogRing {
+    fn write_str(&mut self, s: &str) -> core::fmt::Result {
+        for byte in s.bytes() {
+            self.buf[self.num_written % LOG_RING_SIZE] = byte;
+            self.num_written += 1;
+        }
+        Ok(())
+    }
+}
+
+fn record_in_ring(record: &Record) {
+    let _irq_guard = crate::trap::disable_local();
+
+    // A message may be logged while recording, e.g., if formatting the arguments panics.
+    if IS_RECORDING.load() {
+        return;
+    }
+    IS_RECORDING.store(true);
+
+    let timestamp = Jiffies::elapsed().as_duration().as_secs_f64();
+    let _ = writeln!(
+        LOG_RING.lock(),
+        "[{:>10.3}] {:<5}: {}",
+        timestamp,
+        record.level(),
+        record.args()
+    );
+
+    IS_RECORDING.store(false);
+}
+
+/// Reads the recent log messages, from the oldest to the newest.
+///
+/// The messages are passed to the function in at most two chunks. Nothing is read if the
+/// messages are being recorded on the current CPU.
+pub fn read_recent_logs(mut f: impl FnMut(&[u8])) {
+    if IS_RECORDING.load() {
+        return;
+    }
+    let ring = LOG_RING.lock();
+    let (older, newer) = ring.as_slices();
+    for chunk in [older, newer] {
+        if !chunk.is_empty() {
+            f(chunk);
+        }
+    }
+}
+
 /// Initialize the logger. Users should avoid using the log macros before this function is called.
 pub(crate) fn init() {
     let level = get_log_level().unwrap_or(LevelFilter::Off);
@@ -94,3 +188,25 @@ fn get_log_level() -> Option<LevelFilter> {
 
     LevelFilter::from_str(value).ok()
 }
+
+#[cfg(ktest)]
+mod test {
+    use ostd_macros::ktest;
+
+    use super::*;
+
+    #[ktest]
+    fn log_ring_wraps() {
+        let mut ring = LogRing::new();
+        ring.write_str("hello").unwrap();
+        assert_eq!(ring.as_slices(), (&b"hello"[..], &[][..]));
+
+        for _ in 0..LOG_RING_SIZE / 4 {
+            ring.write_str("0123").unwrap();
+        }
+        ring.write_str("tail").unwrap();
+        let (older, newer) = ring.as_slices();
+        assert_eq!(older.len() + newer.len(), LOG_RING_SIZE);
+        assert!([older, newer].concat().ends_with(b"0123tail"));
+    }
+}
@@ -2,6 +2,8 @@
 
 //! The physical memory allocator.
 
+use core::ops::Range;
+
 use align_ext::AlignExt;
 use buddy_system_allocator::FrameAllocator;
 use log::info;
@@ -9,16 +11,24 @@ use spin::Once;
 
 use super::{meta::AnyFrameMeta, segment::Segment, Frame};
 use crate::{
+    arch::mm::PagingConsts,
     boot::memory_region::MemoryRegionType,
     error::Error,
-    mm::{paddr_to_vaddr, Paddr, PAGE_SIZE},
+    mm::{
+        numa::{self, NodeSet},
+        paddr_to_vaddr, page_size, Paddr, PagingConstsTrait, PagingLevel, PAGE_SIZE,
+    },
     prelude::*,
     sync::SpinLock,
+    trap,
 };
 
 /// Options for allocating physical memory frames.
+#[derive(Clone, Debug)]
 pub struct FrameAllocOptions {
     zeroed: bool,
+    preferred_node: Option<usize>,
+    nodes: Option<NodeSet>,
 }
 
 impl Default for FrameAllocOptions {
@@ -30,7 +40,11 @@ impl Default for FrameAllocOptions {
 impl FrameAllocOptions {
     /// Creates new options for allocating the specified number of frames.
     pub fn new() -> Self {
-        Self { zeroed: true }
+        Self {
+            zeroed: true,
+            preferred_node: None,
+            nodes: None,
+        }
     }
 
     /// Sets whether the allocated frames should be initialized with zeros.
@@ -45,6 +59,25 @@ impl FrameAllocOptions {
         self
     }
 
+    /// Sets the NUMA node that the frames are preferably allocated from.
+    ///
+    /// If the preferred node does not have enough free memory, the frames are allocated from
+    /// other nodes.
+    ///
+    /// By default, the preferred node is the node of the current CPU.
+    pub fn preferred_node(&mut self, node: usize) -> &mut Self {
+        self.preferred_node = Some(node);
+        self
+    }
+
+    /// Restricts the NUMA nodes that the frames can be allocated from.
+    ///
+    /// By default, the frames can be allocated from any node.
+    pub fn nodes(&mut self, nodes: NodeSet) -> &mut Self {
+        self.nodes = Some(nodes);
+        self
+    }
+
     /// Allocates a single untyped frame without metadata.
     pub fn alloc_frame(&self) -> Result<Frame<()>> {
         self.alloc_frame_with(())
@@ -52,12 +85,8 @@ impl FrameAllocOptions {
 
     /// Allocates a single frame with additional metadata.
     pub fn alloc_frame_with<M: AnyFrameMeta>(&self, metadata: M) -> Result<Frame<M>> {
-        let frame = FRAME_ALLOCATOR
-            .get()
-            .unwrap()
-            .disable_irq()
-            .lock()
-            .alloc(1)
+        let frame = self
+            .alloc_frames(1)
             .map(|idx| {
                 let paddr = idx * PAGE_SIZE;
                 Frame::from_unused(paddr, metadata)
@@ -73,6 +102,45 @@ impl FrameAllocOptions {
         Ok(frame)
     }
 
+    /// Allocates a single untyped huge frame of the given paging level without metadata.
+    pub fn alloc_huge_frame(&self, level: PagingLevel) -> Result<Frame<()>> {
+        self.alloc_huge_frame_with(level, ())
+    }
+
+    /// Allocates a single huge frame of the given paging level with additional metadata.
+    ///
+    /// The frame is aligned to its size, so it can be mapped as a huge page.
+    /// The method returns an error if the level is not a valid translation
+    /// level of the page table.
+    pub fn alloc_huge_frame_with<M: AnyFrameMeta>(
+        &self,
+        level: PagingLevel,
+        metadata: M,
+    ) -> Result<Frame<M>> {
+        if level == 0 || level > PagingConsts::HIGHEST_TRANSLATION_LEVEL {
+            return Err(Error::InvalidArgs);
+        }
+        let size = page_size::<PagingConsts>(level);
+
+        // The buddy allocator returns blocks that are aligned to their sizes.
+        let frame = self
+            .alloc_frames(size / PAGE_SIZE)
+            .map(|idx| {
+                let paddr = idx * PAGE_SIZE;
+                debug_assert_eq!(paddr % size, 0);
+                Frame::from_unused_at_level(paddr, level, metadata)
+            })
+            .ok_or(Error::NoMemory)?;
+
+        if self.zeroed {
+            let addr = paddr_to_vaddr(frame.start_paddr()) as *mut u8;
+            // SAFETY: The newly allocated frame is guaranteed to be valid.
+            unsafe { core::ptr::write_bytes(addr, 0, size) }
+        }
+
+        Ok(frame)
+    }
+
     /// Allocates a contiguous range of untyped frames without metadata.
     pub fn alloc_segment(&self, nframes: usize) -> Result<Segment<()>> {
         self.alloc_segment_with(nframes, |_| ())
@@ -93,12 +161,8 @@ impl FrameAllocOptions {
         if nframes == 0 {
             return Err(Error::InvalidArgs);
         }
-        let segment = FRAME_ALLOCATOR
-            .get()
-            .unwrap()
-            .disable_irq()
-            .lock()
-            .alloc(nframes)
+        let segment = self
+            .alloc_frames(nframes)
             .map(|start| {
                 Segment::from_unused(
                     start * PAGE_SIZE..start * PAGE_SIZE + nframes * PAGE_SIZE,
@@ -115,6 +179,20 @@ impl FrameAllocOptions {
 
         Ok(segment)
     }
+
+    /// Allocates `nframes` contiguous frames and returns the index of the first frame.
+    fn alloc_frames(&self, nframes: usize) -> Option<usize> {
+        let irq_guard = trap::disable_local();
+        let preferred_node = self
+            .preferred_node
+            .or_else(|| numa::current_node(&irq_guard));
+
+        FRAME_ALLOCATOR
+            .get()
+            .unwrap()
+            .lock()
+            .alloc_on(nframes, preferred_node, self.nodes.as_ref())
+    }
 }
 
 #[cfg(ktest)]
@@ -140,35 +218,88 @@ fn test_alloc_dealloc() {
 }
 
 /// FrameAllocator with a counter for allocated memory
+///
+/// Each NUMA node has its own [`FrameAllocator`] that manages the free frames in the node.
 pub(in crate::mm) struct CountingFrameAllocator {
-    allocator: FrameAllocator,
+    /// The allocators of the NUMA nodes, indexed by the node IDs.
+    nodes: Vec<NodeFrameAllocator>,
     total: usize,
     allocated: usize,
 }
 
+/// The allocator of the free frames in a NUMA node.
+struct NodeFrameAllocator {
+    allocator: FrameAllocator,
+    /// The ranges of frame indexes that belong to the node.
+    frames: Vec<Range<usize>>,
+}
+
 impl CountingFrameAllocator {
-    pub fn new(allocator: FrameAllocator, total: usize) -> Self {
+    pub fn new(num_nodes: usize) -> Self {
         CountingFrameAllocator {
-            allocator,
-            total,
+            nodes: (0..num_nodes)
+                .map(|_| NodeFrameAllocator {
+                    allocator: FrameAllocator::new(),
+                    frames: Vec::new(),
+                })
+                .collect(),
+            total: 0,
             allocated: 0,
         }
     }
 
+    /// Adds the frames with indexes in `start..end` to the allocator of `node`.
+    fn add_frames(&mut self, node: usize, start: usize, end: usize) {
+        let node = &mut self.nodes[node];
+        node.allocator.add_frame(start, end);
+        node.frames.push(start..end);
+        self.total += (end - start) * PAGE_SIZE;
+    }
+
+    /// Allocates `count` contiguous frames from any NUMA node.
     pub fn alloc(&mut self, count: usize) -> Option<usize> {
-        match self.allocator.alloc(count) {
-            Some(value) => {
-                self.allocated += count * PAGE_SIZE;
-                Some(value)
-            }
-            None => None,
-        }
+        self.alloc_on(count, None, None)
+    }
+
+    /// Allocates `count` contiguous frames from the NUMA nodes in `nodes`.
+    ///
+    /// The preferred node is tried first, and then the other nodes are tried in the ascending
+    /// order of their IDs. If `nodes` is `None`, all nodes can be used.
+    pub fn alloc_on(
+        &mut self,
+        count: usize,
+        preferred_node: Option<usize>,
+        nodes: Option<&NodeSet>,
+    ) -> Option<usize> {
+        let is_allowed = |node: usize| nodes.map_or(true, |nodes| nodes.contains(node));
+
+        let preferred_node = preferred_node.filter(|node| *node < self.nodes.len());
+        let value = preferred_node
+            .into_iter()
+            .chain((0..self.nodes.len()).filter(|node| Some(*node) != preferred_node))
+            .filter(|node| is_allowed(*node))
+            .find_map(|node| self.nodes[node].allocator.alloc(count))?;
+
+        self.allocated += count * PAGE_SIZE;
+        Some(value)
     }
 
     // TODO: this method should be marked unsafe as invalid arguments will mess
     // up the underlying allocator.
     pub fn dealloc(&mut self, start_frame: usize, count: usize) {
-        self.allocator.dealloc(start_frame, count);
+        let node = if self.nodes.len() == 1 {
+            &mut self.nodes[0]
+        } else {
+            self.nodes
+                .iter_mut()
+                .find(|node| {
+                    node.frames
+                        .iter()
+                        .any(|frames| frames.contains(&start_frame))
+                })
+                .unwrap()
+        };
+        node.allocator.dealloc(start_frame, count);
         self.allocated -= count * PAGE_SIZE;
     }
 
@@ -185,20 +316,30 @@ pub(in crate::mm) static FRAME_ALLOCATOR: Once<SpinLock<CountingFrameAllocator>>
 
 pub(crate) fn init() {
     let regions = &crate::boot::EARLY_INFO.get().unwrap().memory_regions;
-    let mut total: usize = 0;
-    let mut allocator = FrameAllocator::<32>::new();
-    for region in regions.iter() {
+    let mut allocator = CountingFrameAllocator::new(numa::num_nodes());
+    // The region reserved for the panic reports must not be allocated.
+    let pstore_region = crate::panic::pstore::reserved_region();
+    let regions = regions.iter().flat_map(|region| match pstore_region {
+        Some(pstore_region) => region.truncate(&pstore_region).to_vec(),
+        None => Vec::from([*region]),
+    });
+    for region in regions {
         if region.typ() == MemoryRegionType::Usable {
             // Make the memory region page-aligned, and skip if it is too small.
-            let start = region.base().align_up(PAGE_SIZE) / PAGE_SIZE;
+            let start = region.base().align_up(PAGE_SIZE);
             let region_end = region.base().checked_add(region.len()).unwrap();
-            let end = region_end.align_down(PAGE_SIZE) / PAGE_SIZE;
+            let end = region_end.align_down(PAGE_SIZE);
             if end <= start {
                 continue;
             }
-            // Add global free pages to the frame allocator.
-            allocator.add_frame(start, end);
-            total += (end - start) * PAGE_SIZE;
+            // Add global free pages to the frame allocators of the NUMA nodes.
+            for (range, node) in numa::split_by_node(start..end) {
+                let start = range.start.align_up(PAGE_SIZE) / PAGE_SIZE;
+                let end = range.end.align_down(PAGE_SIZE) / PAGE_SIZE;
+                if end > start {
+                    allocator.add_frames(node, start, end);
+                }
+            }
             info!(
                 "Found usable region, start:{:x}, end:{:x}",
                 region.base(),
@@ -206,6 +347,5 @@ pub(crate) fn init() {
             );
         }
     }
-    let counting_allocator = CountingFrameAllocator::new(allocator, total);
-    FRAME_ALLOCATOR.call_once(|| SpinLock::new(counting_allocator));
+    FRAME_ALLOCATOR.call_once(|| SpinLock::new(allocator));
 }
@@ -55,15 +55,17 @@ use crate::{
     arch::mm::PagingConsts,
     mm::{
         kspace::LINEAR_MAPPING_BASE_VADDR, paddr_to_vaddr, page_size, page_table::boot_pt,
-        CachePolicy, Infallible, Paddr, PageFlags, PageProperty, PrivilegedPageFlags, Vaddr,
-        VmReader, PAGE_SIZE,
+        CachePolicy, Infallible, Paddr, PageFlags, PageProperty, PagingLevel, PrivilegedPageFlags,
+        Vaddr, VmReader, PAGE_SIZE,
     },
     panic::abort,
 };
 
 /// The maximum number of bytes of the metadata of a frame.
-pub const FRAME_METADATA_MAX_SIZE: usize =
-    META_SLOT_SIZE - size_of::<bool>() - size_of::<AtomicU32>() - size_of::<FrameMetaVtablePtr>();
+pub const FRAME_METADATA_MAX_SIZE: usize = META_SLOT_SIZE
+    - size_of::<PagingLevel>()
+    - size_of::<AtomicU32>()
+    - size_of::<FrameMetaVtablePtr>();
 /// The maximum alignment in bytes of the metadata of a frame.
 pub const FRAME_METADATA_MAX_ALIGN: usize = align_of::<MetaSlot>();
 
@@ -83,6 +85,13 @@ pub(in crate::mm) struct MetaSlot {
     ///
     /// Don't access this field with a reference to the slot.
     _storage: UnsafeCell<[u8; FRAME_METADATA_MAX_SIZE]>,
+    /// The paging level of the frame, which determines its size.
+    ///
+    /// It is written when the frame is constructed ([`Frame::from_unused`])
+    /// or split ([`Frame::split`]). Both happen with exclusive access.
+    ///
+    /// [`Frame::split`]: super::Frame::split
+    pub(super) level: UnsafeCell<MaybeUninit<PagingLevel>>,
     /// The reference count of the page.
     ///
     /// Specifically, the reference count has the following meaning:
@@ -203,12 +212,16 @@ pub(super) unsafe fn drop_last_in_place(ptr: *mut MetaSlot) {
     // SAFETY: The frame metadata is initialized and valid.
     let vtable_ptr = unsafe { vtable_ptr.assume_init_read() };
 
+    // SAFETY: The level is initialized when the frame is constructed.
+    let level = unsafe { (*slot.level.get()).assume_init() };
+    let size = page_size::<PagingConsts>(level);
+
     let meta_ptr: *mut dyn AnyFrameMeta = core::ptr::from_raw_parts_mut(ptr, vtable_ptr);
 
     // SAFETY: The implementer of the frame metadata decides that if the frame
     // is safe to be read or not.
     let mut reader =
-        unsafe { VmReader::from_kernel_space(paddr_to_vaddr(paddr) as *const u8, PAGE_SIZE) };
+        unsafe { VmReader::from_kernel_space(paddr_to_vaddr(paddr) as *const u8, size) };
 
     // SAFETY: `ptr` points to the metadata storage which is valid to be mutably borrowed under
     // `vtable_ptr` because the metadata is valid, the vtable is correct, and we have the exclusive
@@ -232,7 +245,7 @@ pub(super) unsafe fn drop_last_in_place(ptr: *mut MetaSlot) {
         .get()
         .unwrap()
         .lock()
-        .dealloc(paddr / PAGE_SIZE, 1);
+        .dealloc(paddr / PAGE_SIZE, size / PAGE_SIZE);
 }
 
 /// The metadata of frames that holds metadata of frames.
@@ -38,6 +38,7 @@ pub mod untyped;
 
 use core::{
     marker::PhantomData,
+    mem::ManuallyDrop,
     sync::atomic::{AtomicU32, AtomicUsize, Ordering},
 };
 
@@ -48,11 +49,18 @@ use meta::{
 pub use segment::Segment;
 use untyped::{AnyUFrameMeta, UFrame};
 
-use super::{PagingLevel, PAGE_SIZE};
+use super::{page_size, PagingLevel, PAGE_SIZE};
 use crate::mm::{Paddr, PagingConsts, Vaddr};
 
 static MAX_PADDR: AtomicUsize = AtomicUsize::new(0);
 
+/// Returns the maximum physical address that is covered by the frame metadata.
+///
+/// Only the physical memory below this address can be managed as [`Frame`]s.
+pub fn max_paddr() -> Paddr {
+    MAX_PADDR.load(Ordering::Relaxed) as Paddr
+}
+
 /// A smart pointer to a frame.
 ///
 /// A frame is a contiguous range of bytes in physical memory. The [`Frame`]
@@ -87,7 +95,26 @@ impl<M: AnyFrameMeta> Frame<M> {
     ///  - the physical address is out of bound or not aligned;
     ///  - the page is already in use.
     pub fn from_unused(paddr: Paddr, metadata: M) -> Self {
-        assert!(paddr % PAGE_SIZE == 0);
+        Self::from_unused_at_level(paddr, 1, metadata)
+    }
+
+    /// Gets a [`Frame`] of the given paging level from raw, unused pages.
+    ///
+    /// Only the metadata slot of the first base page is used. The slots of
+    /// the other base pages covered by the frame are left unused.
+    ///
+    /// # Panics
+    ///
+    /// The function panics if:
+    ///  - the physical address is out of bound or not aligned to the size
+    ///    of the frame;
+    ///  - the page is already in use.
+    pub(in crate::mm) fn from_unused_at_level(
+        paddr: Paddr,
+        level: PagingLevel,
+        metadata: M,
+    ) -> Self {
+        assert!(paddr % page_size::<PagingConsts>(level) == 0);
         assert!(paddr < MAX_PADDR.load(Ordering::Relaxed) as Paddr);
 
         // Checking unsafe preconditions of the `AnyFrameMeta` trait.
@@ -111,6 +138,8 @@ impl<M: AnyFrameMeta> Frame<M> {
         // borrowed only once.
         let vtable_ptr = unsafe { &mut *slot.vtable_ptr.get() };
         vtable_ptr.write(core::ptr::metadata(&metadata as &dyn AnyFrameMeta));
+        // SAFETY: Same as above.
+        unsafe { (*slot.level.get()).write(level) };
 
         // SAFETY:
         // 1. `ptr` points to the first field of `MetaSlot` (guaranteed by `repr(C)`), which is the
@@ -139,6 +168,56 @@ impl<M: AnyFrameMeta> Frame<M> {
         // metadata after initialization.
         unsafe { &*self.ptr.cast() }
     }
+
+    /// Splits a huge frame into base frames.
+    ///
+    /// The first base frame keeps the metadata of the huge frame. The
+    /// metadata of each of the other base frames is created by `split_meta`,
+    /// which can also adjust the metadata kept by the first one.
+    ///
+    /// The frame can only be split if this is the only handle to it, since
+    /// the other handles (including the mappings in page tables) rely on its
+    /// size. Otherwise, or if the frame is a base frame, the frame is
+    /// returned as is.
+    pub fn split(
+        self,
+        mut split_meta: impl FnMut(&mut M) -> M,
+    ) -> core::result::Result<Segment<M>, Self> {
+        if self.level() == 1 {
+            return Err(self);
+        }
+
+        // `Acquire` pairs with the `Release` in dropping the other handles and ensures that
+        // the accesses through them won't be reordered after the split.
+        if self
+            .slot()
+            .ref_count
+            .compare_exchange(1, 0, Ordering::Acquire, Ordering::Relaxed)
+            .is_err()
+        {
+            return Err(self);
+        }
+
+        let paddr = self.start_paddr();
+        let size = self.size();
+
+        // SAFETY: We have exclusive access to the page metadata, which is valid since the frame
+        // is alive.
+        let meta = unsafe { &mut *self.ptr.cast::<M>().cast_mut() };
+        for tail_paddr in (paddr + PAGE_SIZE..paddr + size).step_by(PAGE_SIZE) {
+            // The metadata slots of the tail pages are unused as long as the huge frame is alive.
+            let tail = Self::from_unused(tail_paddr, split_meta(meta));
+            let _ = ManuallyDrop::new(tail);
+        }
+
+        // SAFETY: We have exclusive access to the page metadata.
+        unsafe { (*self.slot().level.get()).write(1) };
+        self.slot().ref_count.store(1, Ordering::Relaxed);
+        let _ = ManuallyDrop::new(self);
+
+        // SAFETY: A handle to each base frame in the range has been forgotten above.
+        Ok(unsafe { Segment::from_raw(paddr..paddr + size) })
+    }
 }
 
 impl<M: AnyFrameMeta + ?Sized> Frame<M> {
@@ -150,17 +229,18 @@ impl<M: AnyFrameMeta + ?Sized> Frame<M> {
     /// Gets the paging level of this page.
     ///
     /// This is the level of the page table entry that maps the frame,
-    /// which determines the size of the frame.
-    ///
-    /// Currently, the level is always 1, which means the frame is a regular
-    /// page frame.
-    pub const fn level(&self) -> PagingLevel {
-        1
+    /// which determines the size of the frame. Level 1 means that the
+    /// frame is a regular page frame, while higher levels mean that the
+    /// frame is a huge frame.
+    pub fn level(&self) -> PagingLevel {
+        // SAFETY: The level is initialized when the frame is constructed, and it is only changed
+        // with exclusive access to the frame.
+        unsafe { (*self.slot().level.get()).assume_init() }
     }
 
     /// Gets the size of this page in bytes.
-    pub const fn size(&self) -> usize {
-        PAGE_SIZE
+    pub fn size(&self) -> usize {
+        page_size::<PagingConsts>(self.level())
     }
 
     /// Gets the dyncamically-typed metadata of this frame.
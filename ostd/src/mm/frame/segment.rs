@@ -87,6 +87,20 @@ impl<M: AnyFrameMeta> Segment<M> {
 }
 
 impl<M: AnyFrameMeta + ?Sized> Segment<M> {
+    /// Restores a [`Segment`] from the forgotten handles of its frames.
+    ///
+    /// # Safety
+    ///
+    /// For each base frame in the range, there must be a forgotten handle,
+    /// which is owned by the returned [`Segment`] afterwards.
+    pub(super) unsafe fn from_raw(range: Range<Paddr>) -> Self {
+        debug_assert!(range.start % PAGE_SIZE == 0 && range.end % PAGE_SIZE == 0);
+        Self {
+            range,
+            _marker: core::marker::PhantomData,
+        }
+    }
+
     /// Gets the start physical address of the contiguous frames.
     pub fn start_paddr(&self) -> Paddr {
         self.range.start
@@ -160,7 +174,14 @@ impl<M: AnyFrameMeta + ?Sized> Segment<M> {
 }
 
 impl<M: AnyFrameMeta + ?Sized> From<Frame<M>> for Segment<M> {
+    /// Converts a base frame into a [`Segment`].
+    ///
+    /// # Panics
+    ///
+    /// The function panics if the frame is a huge frame. Use [`Frame::split`]
+    /// to split it into a [`Segment`] instead.
     fn from(frame: Frame<M>) -> Self {
+        assert_eq!(frame.level(), 1);
         let pa = frame.start_paddr();
         let _ = ManuallyDrop::new(frame);
         Self {
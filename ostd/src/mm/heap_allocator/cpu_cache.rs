@@ -0,0 +1,211 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! Per-CPU caches of free heap blocks.
+//!
+//! Each CPU keeps a magazine of free blocks for every slab size class. Most
+//! allocations and deallocations of small objects are served by the magazine
+//! of the current CPU without contending on the global heap lock. A magazine is
+//! refilled from, or flushed to, the global heap in batches.
+
+use core::{
+    alloc::Layout,
+    cell::RefCell,
+    sync::atomic::{AtomicBool, Ordering},
+};
+
+use crate::{cpu_local, trap::DisabledLocalIrqGuard};
+
+/// The number of the slab size classes, i.e., 64, 128, ..., 4096 bytes.
+const NR_SIZE_CLASSES: usize = 7;
+const MIN_BLOCK_SIZE: usize = 64;
+const MAX_BLOCK_SIZE: usize = 4096;
+
+/// The maximum number of free blocks in a magazine.
+const MAGAZINE_SIZE: usize = 32;
+/// The number of blocks that are moved between a magazine and the global heap at a time.
+pub(super) const BATCH_SIZE: usize = MAGAZINE_SIZE / 2;
+
+static IS_ENABLED: AtomicBool = AtomicBool::new(false);
+
+cpu_local! {
+    static MAGAZINES: RefCell<[Magazine; NR_SIZE_CLASSES]> =
+        RefCell::new([const { Magazine::new() }; NR_SIZE_CLASSES]);
+}
+
+/// Enables the per-CPU caches.
+///
+/// This function must be called after the CPU-local storages of the APs are
+/// initialized. Otherwise, the blocks cached by the BSP will be copied to the APs.
+pub(super) fn enable() {
+    IS_ENABLED.store(true, Ordering::Relaxed);
+}
+
+/// A slab size class.
+#[derive(Debug, Clone, Copy)]
+pub(super) struct SizeClass(usize);
+
+impl SizeClass {
+    /// Returns the size class that serves `layout`, if the blocks can be cached.
+    pub(super) fn of(layout: &Layout) -> Option<Self> {
+        if !IS_ENABLED.load(Ordering::Relaxed) {
+            return None;
+        }
+
+        let block_size = block_size(layout)?;
+        Some(Self((block_size / MIN_BLOCK_SIZE).trailing_zeros() as usize))
+    }
+
+    /// Returns the size of the blocks in the size class.
+    pub(super) fn block_size(&self) -> usize {
+        MIN_BLOCK_SIZE << self.0
+    }
+
+    /// Returns the layout that allocates a block in the size class from the heap.
+    pub(super) fn block_layout(&self) -> Layout {
+        Layout::from_size_align(self.block_size(), self.block_size()).unwrap()
+    }
+}
+
+/// Returns the size of the slab block that serves `layout`.
+///
+/// It returns `None` if the allocation is not served by slabs.
+fn block_size(layout: &Layout) -> Option<usize> {
+    let size = layout.size().max(layout.align()).max(MIN_BLOCK_SIZE);
+    (size <= MAX_BLOCK_SIZE).then(|| size.next_power_of_two())
+}
+
+/// Takes a free block of the size class from the magazine of the current CPU.
+pub(super) fn pop(guard: &DisabledLocalIrqGuard, class: SizeClass) -> Option<usize> {
+    MAGAZINES.get_with(guard).borrow_mut()[class.0].pop()
+}
+
+/// Puts the free blocks of the size class into the empty magazine of the current CPU.
+pub(super) fn fill(guard: &DisabledLocalIrqGuard, class: SizeClass, blocks: &[usize]) {
+    let mut magazines = MAGAZINES.get_with(guard).borrow_mut();
+    for block in blocks {
+        let is_full = !magazines[class.0].push(*block);
+        debug_assert!(!is_full);
+    }
+}
+
+/// Puts a free block of the size class into the magazine of the current CPU.
+///
+/// If the magazine is full, the oldest [`BATCH_SIZE`] blocks are evicted from the
+/// magazine and returned, which should be given back to the global heap.
+pub(super) fn push(
+    guard: &DisabledLocalIrqGuard,
+    class: SizeClass,
+    block: usize,
+) -> Option<[usize; BATCH_SIZE]> {
+    let mut magazines = MAGAZINES.get_with(guard).borrow_mut();
+    let magazine = &mut magazines[class.0];
+    if magazine.push(block) {
+        return None;
+    }
+
+    let evicted = magazine.evict();
+    magazine.push(block);
+    Some(evicted)
+}
+
+/// Takes all the free blocks out of the magazines of the current CPU.
+///
+/// The blocks of each size class are passed to `f`, which should give them back
+/// to the global heap.
+pub(super) fn drain(guard: &DisabledLocalIrqGuard, mut f: impl FnMut(SizeClass, &[usize])) {
+    let mut magazines = MAGAZINES.get_with(guard).borrow_mut();
+    for (index, magazine) in magazines.iter_mut().enumerate() {
+        f(SizeClass(index), &magazine.blocks[..magazine.len]);
+        magazine.len = 0;
+    }
+}
+
+/// A fixed-size stack of free blocks.
+struct Magazine {
+    blocks: [usize; MAGAZINE_SIZE],
+    len: usize,
+}
+
+impl Magazine {
+    const fn new() -> Self {
+        Self {
+            blocks: [0; MAGAZINE_SIZE],
+            len: 0,
+        }
+    }
+
+    fn pop(&mut self) -> Option<usize> {
+        if self.len == 0 {
+            return None;
+        }
+        self.len -= 1;
+        Some(self.blocks[self.len])
+    }
+
+    /// Pushes a block, returning `false` if the magazine is full.
+    fn push(&mut self, block: usize) -> bool {
+        if self.len == MAGAZINE_SIZE {
+            return false;
+        }
+        self.blocks[self.len] = block;
+        self.len += 1;
+        true
+    }
+
+    /// Removes the [`BATCH_SIZE`] blocks at the bottom of the stack.
+    ///
+    /// The blocks at the top are the most recently freed ones, which are likely
+    /// to be still in the CPU cache. So they are kept in the magazine.
+    fn evict(&mut self) -> [usize; BATCH_SIZE] {
+        debug_assert!(self.len >= BATCH_SIZE);
+
+        let mut evicted = [0; BATCH_SIZE];
+        evicted.copy_from_slice(&self.blocks[..BATCH_SIZE]);
+        self.blocks.copy_within(BATCH_SIZE..self.len, 0);
+        self.len -= BATCH_SIZE;
+        evicted
+    }
+}
+
+#[cfg(ktest)]
+mod test {
+    use alloc::{boxed::Box, vec::Vec};
+
+    use ostd_macros::ktest;
+
+    use super::*;
+
+    #[ktest]
+    fn magazine_evicts_oldest_blocks() {
+        let mut magazine = Magazine::new();
+        for block in 0..MAGAZINE_SIZE {
+            assert!(magazine.push(block));
+        }
+        assert!(!magazine.push(MAGAZINE_SIZE));
+
+        let evicted = magazine.evict();
+        assert!(evicted.iter().copied().eq(0..BATCH_SIZE));
+        assert_eq!(magazine.pop(), Some(MAGAZINE_SIZE - 1));
+    }
+
+    #[ktest]
+    fn size_class_of_layout() {
+        let block_size_of =
+            |size, align| block_size(&Layout::from_size_align(size, align).unwrap());
+        assert_eq!(block_size_of(1, 1), Some(64));
+        assert_eq!(block_size_of(100, 8), Some(128));
+        assert_eq!(block_size_of(8, 512), Some(512));
+        assert_eq!(block_size_of(4096, 8), Some(4096));
+        assert_eq!(block_size_of(4097, 8), None);
+    }
+
+    #[ktest]
+    fn reuse_cached_blocks() {
+        let boxes: Vec<Box<[u8; 100]>> =
+            (0..MAGAZINE_SIZE * 2).map(|_| Box::new([1; 100])).collect();
+        drop(boxes);
+        let boxes: Vec<Box<[u8; 100]>> =
+            (0..MAGAZINE_SIZE * 2).map(|_| Box::new([2; 100])).collect();
+        assert!(boxes.iter().all(|b| b.iter().all(|byte| *byte == 2)));
+    }
+}
@@ -1,10 +1,13 @@
 // SPDX-License-Identifier: MPL-2.0
 
+mod cpu_cache;
+mod poison;
 mod slab_allocator;
 
 use core::alloc::{GlobalAlloc, Layout};
 
 use align_ext::AlignExt;
+use cpu_cache::{SizeClass, BATCH_SIZE};
 use log::debug;
 use slab_allocator::Heap;
 use spin::Once;
@@ -14,7 +17,7 @@ use crate::{
     mm::{frame::allocator::FRAME_ALLOCATOR, PAGE_SIZE},
     prelude::*,
     sync::SpinLock,
-    trap::disable_local,
+    trap::{disable_local, DisabledLocalIrqGuard},
     Error,
 };
 
@@ -43,6 +46,28 @@ pub unsafe fn init() {
         #[allow(static_mut_refs)]
         HEAP_ALLOCATOR.init(HEAP_SPACE.0.as_mut_ptr(), INIT_KERNEL_HEAP_SIZE);
     }
+    poison::init();
+}
+
+/// Enables the per-CPU caches of the heap allocator.
+///
+/// This function should be called after the CPU-local storages are initialized.
+pub(crate) fn enable_cpu_caches() {
+    cpu_cache::enable();
+}
+
+/// Gives the free blocks cached by the current CPU back to the global heap.
+///
+/// This should be called before the current CPU goes offline, since the blocks
+/// cached by an offline CPU cannot be reused by others.
+pub(crate) fn drain_cpu_cache(guard: &DisabledLocalIrqGuard) {
+    let mut heap = HEAP_ALLOCATOR.heap.get().unwrap().lock();
+    cpu_cache::drain(guard, |class, blocks| {
+        for block in blocks {
+            // SAFETY: The cached blocks are free and allocated with the layout of the class.
+            unsafe { heap.deallocate(*block as *mut u8, class.block_layout()) };
+        }
+    });
 }
 
 struct LockedHeapWithRescue {
@@ -124,12 +149,38 @@ impl LockedHeapWithRescue {
 
         Ok(())
     }
-}
 
-unsafe impl GlobalAlloc for LockedHeapWithRescue {
-    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
-        let _guard = disable_local();
+    /// Allocates a block of the size class, preferring the cache of the current CPU.
+    ///
+    /// Returns the block and whether it is taken from the cache.
+    fn alloc_cached(&self, guard: &DisabledLocalIrqGuard, class: SizeClass) -> (*mut u8, bool) {
+        if let Some(block) = cpu_cache::pop(guard, class) {
+            return (block as *mut u8, true);
+        }
+
+        // Refills the cache in a batch to amortize the cost of locking the heap.
+        let layout = class.block_layout();
+        let mut blocks = [0usize; BATCH_SIZE];
+        let nr_blocks = {
+            let mut heap = self.heap.get().unwrap().lock();
+            blocks
+                .iter_mut()
+                .map_while(|slot| {
+                    let (allocation, _) = heap.allocate(layout).ok()?;
+                    *slot = allocation as usize;
+                    Some(())
+                })
+                .count()
+        };
+        let Some((first, rest)) = blocks[..nr_blocks].split_first() else {
+            // Falls back to the slow path, which enlarges the heap if needed.
+            return (self.alloc_from_heap(layout), false);
+        };
+        cpu_cache::fill(guard, class, rest);
+        (*first as *mut u8, false)
+    }
 
+    fn alloc_from_heap(&self, layout: Layout) -> *mut u8 {
         let res = self.heap.get().unwrap().lock().allocate(layout);
         if let Ok((allocation, remain_bytes)) = res {
             self.rescue_if_low_memory(remain_bytes, layout);
@@ -149,13 +200,68 @@ unsafe impl GlobalAlloc for LockedHeapWithRescue {
         }
     }
 
+    /// Frees a block to the cache of the current CPU or to the global heap.
+    ///
+    /// # Safety
+    ///
+    /// The block must be allocated with the layout, and must not be used after it is freed.
+    unsafe fn free_block(&self, guard: &DisabledLocalIrqGuard, ptr: *mut u8, layout: Layout) {
+        let Some(class) = SizeClass::of(&layout) else {
+            // SAFETY: The block is owned by the caller until it is freed.
+            unsafe { poison::on_free(ptr, layout.size()) };
+            self.heap.get().unwrap().lock().deallocate(ptr, layout);
+            return;
+        };
+
+        // SAFETY: The block of the size class is owned by the caller until it is freed.
+        unsafe { poison::on_free(ptr, class.block_size()) };
+        if let Some(evicted) = cpu_cache::push(guard, class, ptr as usize) {
+            let mut heap = self.heap.get().unwrap().lock();
+            for block in evicted {
+                heap.deallocate(block as *mut u8, class.block_layout());
+            }
+        }
+    }
+}
+
+unsafe impl GlobalAlloc for LockedHeapWithRescue {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        let guard = disable_local();
+
+        let (ptr, size, was_cached) = match SizeClass::of(&layout) {
+            Some(class) => {
+                let (ptr, was_cached) = self.alloc_cached(&guard, class);
+                (ptr, class.block_size(), was_cached)
+            }
+            None => (self.alloc_from_heap(layout), layout.size(), false),
+        };
+        if !ptr.is_null() {
+            // SAFETY: The block is just allocated.
+            unsafe { poison::on_alloc(ptr, size, was_cached) };
+            #[cfg(feature = "kasan")]
+            // SAFETY: The block is just allocated.
+            unsafe {
+                super::kasan::on_heap_alloc(ptr, layout.size(), size)
+            };
+        }
+        ptr
+    }
+
     unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
         debug_assert!(ptr as usize != 0);
-        self.heap
-            .get()
-            .unwrap()
-            .disable_irq()
-            .lock()
-            .deallocate(ptr, layout)
+        let guard = disable_local();
+
+        #[cfg(feature = "kasan")]
+        // SAFETY: The block is freed by the caller.
+        unsafe {
+            super::kasan::quarantine(ptr, layout, |ptr, layout| {
+                self.free_block(&guard, ptr, layout)
+            })
+        };
+        #[cfg(not(feature = "kasan"))]
+        // SAFETY: The block is freed by the caller.
+        unsafe {
+            self.free_block(&guard, ptr, layout)
+        };
     }
 }
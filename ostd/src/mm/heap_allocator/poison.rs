@@ -0,0 +1,97 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! Debug poisoning of heap blocks.
+//!
+//! The mode is selected by the `ostd.heap_poison=MODE` kernel command line
+//! argument, where `MODE` is one of the following:
+//!  - `on`: freed blocks are filled with [`POISON_FREE`] and newly allocated
+//!    blocks are filled with [`POISON_INUSE`], which makes reading uninitialized
+//!    or freed memory more visible;
+//!  - `check`: in addition, the blocks taken from the per-CPU caches are checked
+//!    to be intact, which detects writes after free.
+//!
+//! Poisoning is disabled by default.
+
+use core::sync::atomic::{AtomicU8, Ordering};
+
+use crate::boot::EARLY_INFO;
+
+/// The byte pattern of the freed blocks.
+const POISON_FREE: u8 = 0x6b;
+/// The byte pattern of the newly allocated blocks.
+const POISON_INUSE: u8 = 0x5a;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[repr(u8)]
+enum PoisonMode {
+    Off = 0,
+    On = 1,
+    Check = 2,
+}
+
+static MODE: AtomicU8 = AtomicU8::new(PoisonMode::Off as u8);
+
+fn mode() -> PoisonMode {
+    match MODE.load(Ordering::Relaxed) {
+        1 => PoisonMode::On,
+        2 => PoisonMode::Check,
+        _ => PoisonMode::Off,
+    }
+}
+
+/// Initializes the poisoning mode from the kernel command line.
+pub(super) fn init() {
+    let kcmdline = EARLY_INFO.get().unwrap().kernel_cmdline;
+    let mode = match kcmdline
+        .split(' ')
+        .find(|arg| arg.starts_with("ostd.heap_poison="))
+        .map(|arg| arg.split('=').last().unwrap_or_default())
+    {
+        Some("on") => PoisonMode::On,
+        Some("check") => PoisonMode::Check,
+        _ => PoisonMode::Off,
+    };
+    MODE.store(mode as u8, Ordering::Relaxed);
+}
+
+/// Poisons a block that is about to be freed.
+///
+/// # Safety
+///
+/// The memory range `[ptr, ptr + size)` must be owned by the caller.
+pub(super) unsafe fn on_free(ptr: *mut u8, size: usize) {
+    if mode() == PoisonMode::Off {
+        return;
+    }
+    // SAFETY: The caller guarantees that the memory is owned.
+    unsafe { ptr.write_bytes(POISON_FREE, size) };
+}
+
+/// Poisons a newly allocated block.
+///
+/// If `was_cached` is true, the block was freed to the per-CPU caches and has not
+/// been touched since then, so it is checked before being poisoned.
+///
+/// # Safety
+///
+/// The memory range `[ptr, ptr + size)` must be owned by the caller.
+pub(super) unsafe fn on_alloc(ptr: *mut u8, size: usize, was_cached: bool) {
+    let mode = mode();
+    if mode == PoisonMode::Off {
+        return;
+    }
+
+    if mode == PoisonMode::Check && was_cached {
+        // SAFETY: The caller guarantees that the memory is owned.
+        let bytes = unsafe { core::slice::from_raw_parts(ptr, size) };
+        if let Some(offset) = bytes.iter().position(|byte| *byte != POISON_FREE) {
+            panic!(
+                "Heap block {:p} of {} bytes is modified at offset {} after being freed",
+                ptr, size, offset
+            );
+        }
+    }
+
+    // SAFETY: The caller guarantees that the memory is owned.
+    unsafe { ptr.write_bytes(POISON_INUSE, size) };
+}
@@ -0,0 +1,498 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! The kernel address sanitizer (KASAN).
+//!
+//! KASAN detects out-of-bounds accesses and use-after-free bugs in the kernel heap and the
+//! kernel stacks. It is enabled with the `kasan` feature, and the crates to be checked are
+//! compiled with `-Zsanitizer=kernel-address` (see the `KASAN` option of the Makefile).
+//! OSTD itself is not instrumented, so only the accesses made by the instrumented crates
+//! (e.g., the VFS and the drivers) are checked.
+//!
+//! Each 8-byte granule of the memory has a shadow byte, which tells how many bytes at the
+//! start of the granule can be accessed:
+//!  - `0` means that all the 8 bytes can be accessed;
+//!  - `1..=7` means that only the first N bytes can be accessed;
+//!  - a negative value means that the granule cannot be accessed, and the value tells why,
+//!    e.g., [`HEAP_REDZONE`] and [`HEAP_FREED`].
+//!
+//! The shadow byte of the address `addr` is at `(addr >> 3) + SHADOW_OFFSET`, which is the
+//! same as the generic KASAN of Linux on x86-64. With the 48-bit address width, the shadow
+//! memory of the kernel address space is in the unused hole of the kernel address space
+//! (see [`crate::mm::kspace`]). The shadow memory is mapped for the following areas, and the
+//! accesses to the other areas are not checked:
+//!  - the linear mapping of the physical memory, where the heap and the boot stacks are;
+//!  - the kernel image, where the initial heap and the boot stack of the BSP are;
+//!  - the kernel stacks in [`KVirtArea<Tracked>`], for which the shadow memory is mapped when
+//!    they are allocated.
+//!
+//! The compiler calls the `__asan_*` functions defined here to check the accesses, and
+//! poisons the redzones around the variables on the stacks. The heap allocator poisons the
+//! redzones after the heap blocks and the freed blocks, which are put into a quarantine
+//! for a while before being reused.
+//!
+//! [`KVirtArea<Tracked>`]: crate::mm::kspace::kvirt_area::KVirtArea
+
+mod quarantine;
+mod report;
+
+use core::{
+    ops::Range,
+    sync::atomic::{AtomicUsize, Ordering},
+};
+
+use align_ext::AlignExt;
+use log::info;
+use spin::Once;
+
+pub(super) use self::quarantine::quarantine;
+use crate::{
+    boot::{memory_region::MemoryRegionType, EARLY_INFO},
+    impl_frame_meta_for,
+    mm::{
+        kspace::{
+            kernel_loaded_offset, KERNEL_PAGE_TABLE, LINEAR_MAPPING_BASE_VADDR,
+            TRACKED_MAPPED_PAGES_RANGE,
+        },
+        page_prop::{CachePolicy, PageFlags, PageProperty, PrivilegedPageFlags},
+        FrameAllocOptions, Vaddr, PAGE_SIZE,
+    },
+    sync::SpinLock,
+    task::current_kernel_stack,
+};
+
+/// The number of bytes covered by a shadow byte, in bits.
+const SHADOW_SCALE_SHIFT: usize = 3;
+/// The number of bytes covered by a shadow byte.
+const GRANULE_SIZE: usize = 1 << SHADOW_SCALE_SHIFT;
+/// The number of bytes covered by a page of the shadow memory.
+const SHADOW_PAGE_COVERAGE: usize = PAGE_SIZE << SHADOW_SCALE_SHIFT;
+
+/// The offset of the shadow memory.
+///
+/// This must match the `-asan-mapping-offset` option that the crates are compiled with.
+const SHADOW_OFFSET: usize = 0xdfff_fc00_0000_0000;
+
+// The values of the shadow bytes of the inaccessible granules, which are the same as Linux.
+const STACK_GUARD: u8 = 0xfe;
+const HEAP_REDZONE: u8 = 0xfc;
+const HEAP_FREED: u8 = 0xfb;
+const STACK_LEFT: u8 = 0xf1;
+const STACK_MID: u8 = 0xf2;
+const STACK_RIGHT: u8 = 0xf3;
+const STACK_USE_AFTER_SCOPE: u8 = 0xf8;
+const ALLOCA_LEFT: u8 = 0xca;
+const ALLOCA_RIGHT: u8 = 0xcb;
+
+/// The size of the redzones around the dynamic allocations on the stacks.
+const ALLOCA_REDZONE_SIZE: usize = 32;
+
+/// The areas whose shadow memory is mapped at the initialization.
+struct ShadowedAreas {
+    linear_mapping: Range<Vaddr>,
+    kernel_image: Range<Vaddr>,
+}
+
+static SHADOWED_AREAS: Once<ShadowedAreas> = Once::new();
+
+/// The part of [`TRACKED_MAPPED_PAGES_RANGE`] whose shadow memory is mapped.
+///
+/// The kernel virtual areas are allocated downwards from a random base in the range (see
+/// [`crate::mm::kspace::kaslr`]), so the shadow memory is mapped for the range that covers all
+/// the areas that have ever been allocated. The range is empty if both ends are zero.
+static TRACKED_SHADOWED_START: AtomicUsize = AtomicUsize::new(0);
+static TRACKED_SHADOWED_END: AtomicUsize = AtomicUsize::new(0);
+
+/// Serializes the mapping of the shadow memory of [`TRACKED_MAPPED_PAGES_RANGE`].
+static TRACKED_SHADOW_LOCK: SpinLock<()> = SpinLock::new(());
+
+#[derive(Debug, Default)]
+struct ShadowMeta;
+
+impl_frame_meta_for!(ShadowMeta);
+
+/// Maps the shadow memory and enables the checks.
+///
+/// This function should be called after the kernel page table is activated, and before any
+/// kernel stack is allocated.
+pub(crate) fn init() {
+    let regions = &EARLY_INFO.get().unwrap().memory_regions;
+    let phys_mem_cap = regions.iter().map(|r| r.base() + r.len()).max().unwrap();
+    let linear_mapping = LINEAR_MAPPING_BASE_VADDR
+        ..(LINEAR_MAPPING_BASE_VADDR + phys_mem_cap).align_up(SHADOW_PAGE_COVERAGE);
+
+    let kernel_region = regions
+        .iter()
+        .find(|r| r.typ() == MemoryRegionType::Kernel)
+        .unwrap();
+    let kernel_start = kernel_region.base() + kernel_loaded_offset();
+    let kernel_image = kernel_start.align_down(SHADOW_PAGE_COVERAGE)
+        ..(kernel_start + kernel_region.len()).align_up(SHADOW_PAGE_COVERAGE);
+
+    map_shadow(&linear_mapping);
+    map_shadow(&kernel_image);
+    info!(
+        "KASAN: mapped {} MiB of shadow memory",
+        (linear_mapping.len() + kernel_image.len()) / GRANULE_SIZE / (1024 * 1024)
+    );
+
+    SHADOWED_AREAS.call_once(|| ShadowedAreas {
+        linear_mapping,
+        kernel_image,
+    });
+}
+
+/// Returns whether the checks are enabled.
+pub(crate) fn is_enabled() -> bool {
+    SHADOWED_AREAS.is_completed()
+}
+
+/// Maps zeroed shadow memory for the memory range.
+///
+/// The range must be aligned to [`SHADOW_PAGE_COVERAGE`], and its shadow memory must have
+/// not been mapped.
+fn map_shadow(range: &Range<Vaddr>) {
+    let shadow = shadow_addr(range.start)..shadow_addr(range.end);
+    let prop = PageProperty {
+        flags: PageFlags::RW,
+        cache: CachePolicy::Writeback,
+        priv_flags: PrivilegedPageFlags::GLOBAL,
+    };
+    let page_table = KERNEL_PAGE_TABLE.get().unwrap();
+    let mut cursor = page_table.cursor_mut(&shadow).unwrap();
+    for _ in shadow.step_by(PAGE_SIZE) {
+        let frame = FrameAllocOptions::new()
+            .alloc_frame_with(ShadowMeta)
+            .expect("Failed to allocate the KASAN shadow memory");
+        // SAFETY: The shadow memory is in the unused hole of the kernel address space, and
+        // it is only accessed by KASAN.
+        unsafe {
+            let _old = cursor.map(frame.into(), prop);
+        }
+    }
+}
+
+/// Maps the shadow memory for a kernel stack and poisons its guard pages.
+///
+/// `area` is the range of the kernel virtual area of the stack, and `stack` is the range of
+/// the mapped stack pages in it.
+pub(crate) fn on_kernel_stack_alloc(area: Range<Vaddr>, stack: Range<Vaddr>) {
+    if !is_enabled() {
+        return;
+    }
+    debug_assert!(TRACKED_MAPPED_PAGES_RANGE.contains(&area.start));
+
+    {
+        let _guard = TRACKED_SHADOW_LOCK.lock();
+        let new_start = area.start.align_down(SHADOW_PAGE_COVERAGE);
+        let new_end = area.end.align_up(SHADOW_PAGE_COVERAGE);
+        let shadowed_start = TRACKED_SHADOWED_START.load(Ordering::Relaxed);
+        let shadowed_end = TRACKED_SHADOWED_END.load(Ordering::Relaxed);
+        if shadowed_start == shadowed_end {
+            map_shadow(&(new_start..new_end));
+            TRACKED_SHADOWED_START.store(new_start, Ordering::Release);
+            TRACKED_SHADOWED_END.store(new_end, Ordering::Release);
+        } else {
+            if new_start < shadowed_start {
+                map_shadow(&(new_start..shadowed_start));
+                TRACKED_SHADOWED_START.store(new_start, Ordering::Release);
+            }
+            if new_end > shadowed_end {
+                map_shadow(&(shadowed_end..new_end));
+                TRACKED_SHADOWED_END.store(new_end, Ordering::Release);
+            }
+        }
+    }
+
+    // SAFETY: The kernel virtual area is owned by the new kernel stack.
+    unsafe {
+        poison(area.start, stack.start - area.start, STACK_GUARD);
+        unpoison(stack.start, stack.len());
+        poison(stack.end, area.end - stack.end, STACK_GUARD);
+    }
+}
+
+/// Unpoisons a newly allocated heap block and poisons the redzone after it.
+///
+/// `size` is the requested size, and `block_size` is the size of the allocated block.
+///
+/// # Safety
+///
+/// The block `[ptr, ptr + block_size)` must be owned by the caller.
+pub(super) unsafe fn on_heap_alloc(ptr: *mut u8, size: usize, block_size: usize) {
+    if !is_enabled() {
+        return;
+    }
+    let addr = ptr as Vaddr;
+    let redzone_start = (addr + size).align_up(GRANULE_SIZE);
+    // SAFETY: The caller guarantees that the block is owned.
+    unsafe {
+        unpoison(addr, size);
+        poison(
+            redzone_start,
+            addr + block_size - redzone_start,
+            HEAP_REDZONE,
+        );
+    }
+}
+
+/// Returns the address of the shadow byte of the address.
+const fn shadow_addr(addr: Vaddr) -> Vaddr {
+    (addr >> SHADOW_SCALE_SHIFT).wrapping_add(SHADOW_OFFSET)
+}
+
+/// Returns whether the shadow byte of the address is mapped.
+fn is_shadowed(addr: Vaddr) -> bool {
+    let Some(areas) = SHADOWED_AREAS.get() else {
+        return false;
+    };
+    areas.linear_mapping.contains(&addr)
+        || areas.kernel_image.contains(&addr)
+        || (TRACKED_SHADOWED_START.load(Ordering::Acquire)
+            ..TRACKED_SHADOWED_END.load(Ordering::Acquire))
+            .contains(&addr)
+}
+
+fn read_shadow(addr: Vaddr) -> u8 {
+    // SAFETY: The callers only read the shadow bytes of the shadowed addresses, which are
+    // always mapped.
+    unsafe { (shadow_addr(addr) as *const u8).read_volatile() }
+}
+
+/// Poisons the granules in `[addr, addr + size)` with the value.
+///
+/// `addr` must be aligned to [`GRANULE_SIZE`], and `size` is rounded up to it.
+///
+/// # Safety
+///
+/// The memory range must be owned by the caller.
+unsafe fn poison(addr: Vaddr, size: usize, value: u8) {
+    debug_assert!(addr % GRANULE_SIZE == 0);
+    if size == 0 || !is_shadowed(addr) {
+        return;
+    }
+    let len = size.align_up(GRANULE_SIZE) >> SHADOW_SCALE_SHIFT;
+    // SAFETY: The shadow bytes of the memory owned by the caller are only written by the
+    // caller.
+    unsafe { (shadow_addr(addr) as *mut u8).write_bytes(value, len) };
+}
+
+/// Unpoisons the bytes in `[addr, addr + size)`.
+///
+/// `addr` must be aligned to [`GRANULE_SIZE`]. If `size` is not aligned, the rest of the last
+/// granule is left inaccessible.
+///
+/// # Safety
+///
+/// The memory range must be owned by the caller.
+unsafe fn unpoison(addr: Vaddr, size: usize) {
+    debug_assert!(addr % GRANULE_SIZE == 0);
+    if size == 0 || !is_shadowed(addr) {
+        return;
+    }
+    let shadow = shadow_addr(addr) as *mut u8;
+    let len = size >> SHADOW_SCALE_SHIFT;
+    // SAFETY: The shadow bytes of the memory owned by the caller are only written by the
+    // caller.
+    unsafe {
+        shadow.write_bytes(0, len);
+        if size % GRANULE_SIZE != 0 {
+            shadow.add(len).write((size % GRANULE_SIZE) as u8);
+        }
+    }
+}
+
+/// Finds the first inaccessible byte in `[addr, addr + size)`.
+fn find_poisoned(addr: Vaddr, size: usize) -> Option<Vaddr> {
+    let last = addr + size - 1;
+    let mut granule = addr.align_down(GRANULE_SIZE);
+    while granule <= last {
+        let shadow = read_shadow(granule);
+        if shadow != 0 {
+            // The first inaccessible byte of the granule.
+            let bad_addr = if (shadow as i8) < 0 {
+                granule
+            } else {
+                granule + shadow as usize
+            };
+            if bad_addr <= last {
+                return Some(bad_addr.max(addr));
+            }
+        }
+        granule += GRANULE_SIZE;
+    }
+    None
+}
+
+/// Checks an access made by the instrumented code.
+#[inline(always)]
+fn check_access(addr: Vaddr, size: usize, is_write: bool, is_fatal: bool) {
+    if size == 0 || !is_enabled() {
+        return;
+    }
+    let Some(last) = addr.checked_add(size - 1) else {
+        return;
+    };
+    if !is_shadowed(addr) || !is_shadowed(last) {
+        return;
+    }
+    if let Some(bad_addr) = find_poisoned(addr, size) {
+        report::report_bad_access(bad_addr, addr, size, is_write);
+        if is_fatal {
+            panic!("KASAN: bad access at {:#x}", bad_addr);
+        }
+    }
+}
+
+macro_rules! define_access_checks {
+    ($($size:literal => $load:ident, $load_noabort:ident, $store:ident, $store_noabort:ident;)*) => {
+        $(
+            #[no_mangle]
+            extern "C" fn $load(addr: Vaddr) {
+                check_access(addr, $size, false, true);
+            }
+
+            #[no_mangle]
+            extern "C" fn $load_noabort(addr: Vaddr) {
+                check_access(addr, $size, false, false);
+            }
+
+            #[no_mangle]
+            extern "C" fn $store(addr: Vaddr) {
+                check_access(addr, $size, true, true);
+            }
+
+            #[no_mangle]
+            extern "C" fn $store_noabort(addr: Vaddr) {
+                check_access(addr, $size, true, false);
+            }
+        )*
+    };
+}
+
+define_access_checks! {
+    1 => __asan_load1, __asan_load1_noabort, __asan_store1, __asan_store1_noabort;
+    2 => __asan_load2, __asan_load2_noabort, __asan_store2, __asan_store2_noabort;
+    4 => __asan_load4, __asan_load4_noabort, __asan_store4, __asan_store4_noabort;
+    8 => __asan_load8, __asan_load8_noabort, __asan_store8, __asan_store8_noabort;
+    16 => __asan_load16, __asan_load16_noabort, __asan_store16, __asan_store16_noabort;
+}
+
+#[no_mangle]
+extern "C" fn __asan_loadN(addr: Vaddr, size: usize) {
+    check_access(addr, size, false, true);
+}
+
+#[no_mangle]
+extern "C" fn __asan_loadN_noabort(addr: Vaddr, size: usize) {
+    check_access(addr, size, false, false);
+}
+
+#[no_mangle]
+extern "C" fn __asan_storeN(addr: Vaddr, size: usize) {
+    check_access(addr, size, true, true);
+}
+
+#[no_mangle]
+extern "C" fn __asan_storeN_noabort(addr: Vaddr, size: usize) {
+    check_access(addr, size, true, false);
+}
+
+/// Unpoisons the part of the current stack below the caller.
+///
+/// The compiler calls this function before calling a function that does not return, e.g.,
+/// when a panic starts to unwind. The stack frames that are unwound do not unpoison their
+/// redzones, which would be reported if the stack is reused by the uninstrumented code.
+#[no_mangle]
+extern "C" fn __asan_handle_no_return() {
+    if !is_enabled() {
+        return;
+    }
+    let Some(stack) = current_kernel_stack() else {
+        return;
+    };
+    let marker = 0u8;
+    let sp = (&marker as *const u8 as Vaddr).align_down(GRANULE_SIZE);
+    if stack.contains(&sp) {
+        // SAFETY: The part of the current stack below the current function is not used.
+        unsafe { unpoison(stack.start, sp - stack.start) };
+    }
+}
+
+macro_rules! define_set_shadow {
+    ($($name:ident => $value:literal,)*) => {
+        $(
+            /// Fills the shadow bytes of a stack frame.
+            ///
+            /// The compiler calls this function with the address of the shadow bytes, rather
+            /// than the address of the memory.
+            #[no_mangle]
+            extern "C" fn $name(shadow: Vaddr, len: usize) {
+                // SAFETY: The compiler only fills the shadow bytes of the current stack frame.
+                unsafe { (shadow as *mut u8).write_bytes($value, len) };
+            }
+        )*
+    };
+}
+
+define_set_shadow! {
+    __asan_set_shadow_00 => 0x00,
+    __asan_set_shadow_f1 => 0xf1,
+    __asan_set_shadow_f2 => 0xf2,
+    __asan_set_shadow_f3 => 0xf3,
+    __asan_set_shadow_f5 => 0xf5,
+    __asan_set_shadow_f8 => 0xf8,
+}
+
+/// Poisons the redzones around a dynamic allocation on the stack.
+#[no_mangle]
+extern "C" fn __asan_alloca_poison(addr: Vaddr, size: usize) {
+    let rounded_up_size = size.align_up(GRANULE_SIZE);
+    let padding_size = size.align_up(ALLOCA_REDZONE_SIZE) - rounded_up_size;
+    // SAFETY: The compiler allocates the redzones around the allocation in the current stack
+    // frame.
+    unsafe {
+        poison(addr - ALLOCA_REDZONE_SIZE, ALLOCA_REDZONE_SIZE, ALLOCA_LEFT);
+        unpoison(addr, size);
+        poison(
+            addr + rounded_up_size,
+            padding_size + ALLOCA_REDZONE_SIZE,
+            ALLOCA_RIGHT,
+        );
+    }
+}
+
+/// Unpoisons the dynamic allocations on the stack that are freed.
+#[no_mangle]
+extern "C" fn __asan_allocas_unpoison(top: Vaddr, bottom: Vaddr) {
+    if top == 0 || top > bottom {
+        return;
+    }
+    // SAFETY: The allocations in `[top, bottom)` of the current stack frame are freed.
+    unsafe { unpoison(top, bottom - top) };
+}
+
+#[cfg(ktest)]
+mod test {
+    use alloc::boxed::Box;
+
+    use ostd_macros::ktest;
+
+    use super::*;
+
+    #[ktest]
+    fn heap_redzone_and_free() {
+        if !is_enabled() {
+            return;
+        }
+
+        let block = Box::new([0u8; 13]);
+        let addr = block.as_ptr() as Vaddr;
+        assert_eq!(find_poisoned(addr, 13), None);
+        assert_eq!(find_poisoned(addr + 8, 8), Some(addr + 13));
+        assert_eq!(find_poisoned(addr + 14, 1), Some(addr + 14));
+
+        drop(block);
+        assert_eq!(find_poisoned(addr, 1), Some(addr));
+        assert_eq!(read_shadow(addr), HEAP_FREED);
+    }
+}
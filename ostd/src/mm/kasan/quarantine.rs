@@ -0,0 +1,70 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! The quarantine of the freed heap blocks.
+//!
+//! A freed block is poisoned and kept in the quarantine, until enough blocks are freed after
+//! it. This delays the reuse of the block, so that the accesses to the block after it is freed
+//! are more likely to be detected.
+
+use core::alloc::Layout;
+
+use super::{is_enabled, poison, HEAP_FREED};
+use crate::{
+    mm::Vaddr,
+    sync::{LocalIrqDisabled, SpinLock},
+};
+
+/// The maximum number of the blocks in the quarantine.
+const QUARANTINE_CAPACITY: usize = 1024;
+
+static QUARANTINE: SpinLock<Quarantine, LocalIrqDisabled> = SpinLock::new(Quarantine::new());
+
+/// A FIFO queue of the freed blocks.
+struct Quarantine {
+    blocks: [Option<(Vaddr, Layout)>; QUARANTINE_CAPACITY],
+    /// The index of the oldest block.
+    head: usize,
+}
+
+impl Quarantine {
+    const fn new() -> Self {
+        Self {
+            blocks: [None; QUARANTINE_CAPACITY],
+            head: 0,
+        }
+    }
+
+    /// Puts a block into the quarantine, returning the oldest block if it is full.
+    fn push(&mut self, block: (Vaddr, Layout)) -> Option<(Vaddr, Layout)> {
+        let evicted = self.blocks[self.head].replace(block);
+        self.head = (self.head + 1) % QUARANTINE_CAPACITY;
+        evicted
+    }
+}
+
+/// Poisons a freed block and puts it into the quarantine.
+///
+/// The block is freed with `free` if the checks are not enabled. Otherwise, the oldest block
+/// in the quarantine is freed with `free` if the quarantine is full.
+///
+/// # Safety
+///
+/// The block must be allocated by the heap allocator with the layout, and must not be used
+/// after this function is called.
+pub(in crate::mm) unsafe fn quarantine(
+    ptr: *mut u8,
+    layout: Layout,
+    free: impl FnOnce(*mut u8, Layout),
+) {
+    if !is_enabled() {
+        free(ptr, layout);
+        return;
+    }
+
+    // SAFETY: The block is freed by the caller, so it is owned by the heap allocator.
+    unsafe { poison(ptr as Vaddr, layout.size(), HEAP_FREED) };
+    let evicted = QUARANTINE.lock().push((ptr as Vaddr, layout));
+    if let Some((addr, layout)) = evicted {
+        free(addr as *mut u8, layout);
+    }
+}
@@ -0,0 +1,61 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! The reports of the bad accesses.
+
+use align_ext::AlignExt;
+
+use super::{
+    read_shadow, ALLOCA_LEFT, ALLOCA_RIGHT, GRANULE_SIZE, HEAP_FREED, HEAP_REDZONE, STACK_GUARD,
+    STACK_LEFT, STACK_MID, STACK_RIGHT, STACK_USE_AFTER_SCOPE,
+};
+use crate::{cpu_local_cell, early_println, mm::Vaddr, panic::print_stack_trace, trap};
+
+cpu_local_cell! {
+    /// Whether a bad access is being reported on the CPU.
+    static IS_REPORTING: bool = false;
+}
+
+/// Reports a bad access of `size` bytes at `addr`, where `bad_addr` is the first byte that
+/// cannot be accessed.
+#[cold]
+pub(super) fn report_bad_access(bad_addr: Vaddr, addr: Vaddr, size: usize, is_write: bool) {
+    let _irq_guard = trap::disable_local();
+    // The code that prints the report may be instrumented, e.g., the console drivers.
+    if IS_REPORTING.load() {
+        return;
+    }
+    IS_REPORTING.store(true);
+
+    let mut shadow = read_shadow(bad_addr);
+    if (shadow as i8) > 0 {
+        // The bad byte is at the end of a partially accessible granule. The next granule tells
+        // what the bytes after the accessible part are.
+        shadow = read_shadow(bad_addr.align_up(GRANULE_SIZE));
+    }
+
+    early_println!("==================================================================");
+    early_println!("BUG: KASAN: {} at {:#x}", describe(shadow), bad_addr);
+    early_println!(
+        "{} of size {} at {:#x}, the shadow byte is {:#04x}",
+        if is_write { "Write" } else { "Read" },
+        size,
+        addr,
+        shadow
+    );
+    print_stack_trace();
+    early_println!("==================================================================");
+
+    IS_REPORTING.store(false);
+}
+
+fn describe(shadow: u8) -> &'static str {
+    match shadow {
+        HEAP_REDZONE => "slab-out-of-bounds",
+        HEAP_FREED => "use-after-free",
+        STACK_LEFT | STACK_MID | STACK_RIGHT => "stack-out-of-bounds",
+        STACK_USE_AFTER_SCOPE => "stack-use-after-scope",
+        STACK_GUARD => "stack-guard-page-access",
+        ALLOCA_LEFT | ALLOCA_RIGHT => "alloca-out-of-bounds",
+        _ => "out-of-bounds",
+    }
+}
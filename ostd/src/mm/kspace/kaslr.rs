@@ -0,0 +1,151 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! Kernel address space layout randomization (KASLR).
+//!
+//! The following addresses are randomly chosen at each boot, so that they are
+//! not predictable:
+//!  - The virtual address of the kernel image. On x86-64, the kernel is linked
+//!    at a fixed address, but the boot code maps the kernel image at the
+//!    address plus a random offset aligned to 2 MiB, and relocates the kernel
+//!    image with the relocation table that OSDK embeds into the kernel (see
+//!    `ostd/src/arch/x86/boot/bsp_boot.S`). See [`kaslr_offset`].
+//!  - The bases of the kernel virtual areas ([`KVirtArea`]), where the kernel
+//!    stacks and the I/O memory mappings are allocated. See [`random_base`].
+//!
+//! The randomization can be disabled with the `nokaslr` kernel command line
+//! argument, like Linux. The linear mapping still has a fixed base, and the
+//! kernel image is not relocated on the other architectures.
+//!
+//! The random values are taken from the hardware random number generator
+//! (i.e., `RDSEED` or `RDRAND` on x86-64). If it is not available, the
+//! randomization is disabled with a warning instead of using predictable
+//! values.
+//!
+//! [`KVirtArea`]: super::kvirt_area::KVirtArea
+
+use core::{
+    ops::Range,
+    sync::atomic::{AtomicBool, Ordering},
+};
+
+use cfg_if::cfg_if;
+use log::warn;
+
+use crate::{boot::EARLY_INFO, mm::Vaddr};
+
+/// The granularity of the random bases, which is the size of a 1 GiB huge page.
+const KASLR_ALIGN: usize = 1 << 30;
+
+static ENABLED: AtomicBool = AtomicBool::new(true);
+
+/// Initializes KASLR from the kernel command line.
+pub(super) fn init() {
+    let kcmdline = EARLY_INFO.get().unwrap().kernel_cmdline;
+    if kcmdline.split(' ').any(|arg| arg == "nokaslr") {
+        ENABLED.store(false, Ordering::Relaxed);
+        return;
+    }
+
+    if random_u64().is_none() {
+        warn!("KASLR is disabled since no hardware random number generator is available");
+        ENABLED.store(false, Ordering::Relaxed);
+    }
+}
+
+/// Returns whether KASLR is enabled.
+pub fn is_enabled() -> bool {
+    ENABLED.load(Ordering::Relaxed)
+}
+
+/// Returns the offset of the virtual address of the kernel image.
+///
+/// The kernel image is mapped at the addresses that the kernel is linked at
+/// plus the offset. The offset is zero if the kernel image is not relocated.
+pub fn kaslr_offset() -> usize {
+    cfg_if! {
+        if #[cfg(target_arch = "x86_64")] {
+            extern "C" {
+                static __kernel_image_offset: usize;
+            }
+            // SAFETY: The offset is written by the boot code before any Rust code is executed,
+            // and it is never written again.
+            unsafe { core::ptr::addr_of!(__kernel_image_offset).read() }
+        } else {
+            0
+        }
+    }
+}
+
+/// Returns a random base in the range, which is aligned to [`KASLR_ALIGN`].
+///
+/// The range itself is returned if KASLR is disabled, the range is too small
+/// to be randomized, or no random value can be generated.
+pub(super) fn random_base(range: &Range<Vaddr>) -> Vaddr {
+    let nr_slots = range.len() / KASLR_ALIGN;
+    if !is_enabled() || nr_slots <= 1 {
+        return range.start;
+    }
+
+    let Some(value) = random_u64() else {
+        warn!("KASLR failed to generate a random base for {:#x?}", range);
+        return range.start;
+    };
+    range.start + (value as usize % nr_slots) * KASLR_ALIGN
+}
+
+/// Generates a random value with the hardware random number generator.
+///
+/// The values derived from the time (e.g., the TSC) are never used, since
+/// they are predictable at boot time.
+fn random_u64() -> Option<u64> {
+    cfg_if! {
+        if #[cfg(target_arch = "x86_64")] {
+            crate::arch::read_random_seed().or_else(crate::arch::read_random)
+        } else {
+            None
+        }
+    }
+}
+
+#[cfg(ktest)]
+mod test {
+    use ostd_macros::ktest;
+
+    use super::*;
+    use crate::mm::kspace::{TRACKED_MAPPED_PAGES_RANGE, VMALLOC_VADDR_RANGE};
+
+    #[ktest]
+    fn random_base_in_range() {
+        for range in [TRACKED_MAPPED_PAGES_RANGE, VMALLOC_VADDR_RANGE] {
+            let base = random_base(&range);
+            assert!(range.contains(&base));
+            assert_eq!((base - range.start) % KASLR_ALIGN, 0);
+        }
+    }
+
+    #[ktest]
+    fn kernel_image_offset() {
+        // The offset is aligned to 2 MiB, see `KASLR_ALIGN_SHIFT` in `bsp_boot.S`.
+        assert_eq!(kaslr_offset() % (1 << 21), 0);
+        assert_eq!(
+            crate::mm::kspace::kernel_loaded_offset(),
+            crate::mm::kspace::KERNEL_CODE_BASE_VADDR + kaslr_offset()
+        );
+    }
+
+    #[ktest]
+    fn kernel_image_relocated() {
+        static TARGET: u8 = 0;
+        static POINTER: &u8 = &TARGET;
+
+        // The address in `POINTER` is computed at link time, so it is correct only if the
+        // kernel image is relocated with the same offset that it is mapped with.
+        assert_eq!(POINTER as *const u8, &TARGET as *const u8);
+    }
+
+    #[ktest]
+    fn small_range_not_randomized() {
+        let range = 0x1000_0000..0x1000_0000 + KASLR_ALIGN;
+        assert_eq!(random_base(&range), range.start);
+    }
+}
@@ -2,12 +2,12 @@
 
 //! Kernel virtual memory allocation
 
-use alloc::collections::BTreeMap;
+use alloc::{collections::BTreeMap, vec::Vec};
 use core::{any::TypeId, marker::PhantomData, ops::Range};
 
 use align_ext::AlignExt;
 
-use super::{KERNEL_PAGE_TABLE, TRACKED_MAPPED_PAGES_RANGE, VMALLOC_VADDR_RANGE};
+use super::{kaslr, KERNEL_PAGE_TABLE, TRACKED_MAPPED_PAGES_RANGE, VMALLOC_VADDR_RANGE};
 use crate::{
     cpu::CpuSet,
     mm::{
@@ -17,7 +17,7 @@ use crate::{
         tlb::{TlbFlushOp, TlbFlusher, FLUSH_ALL_RANGE_THRESHOLD},
         Paddr, Vaddr, PAGE_SIZE,
     },
-    sync::SpinLock,
+    sync::{LocalIrqDisabled, SpinLock},
     task::disable_preempt,
     Error, Result,
 };
@@ -35,6 +35,8 @@ impl KVirtAreaFreeNode {
 pub struct VirtAddrAllocator {
     fullrange: Range<Vaddr>,
     freelist: SpinLock<Option<BTreeMap<Vaddr, KVirtAreaFreeNode>>>,
+    /// The areas that are dropped with IRQs disabled and have not been unmapped.
+    deferred: SpinLock<Vec<Range<Vaddr>>, LocalIrqDisabled>,
 }
 
 impl VirtAddrAllocator {
@@ -42,20 +44,29 @@ impl VirtAddrAllocator {
         Self {
             fullrange,
             freelist: SpinLock::new(None),
+            deferred: SpinLock::new(Vec::new()),
         }
     }
 
     /// Allocates a kernel virtual area.
     ///
     /// This is currently implemented with a simple FIRST-FIT algorithm.
+    ///
+    /// The full range is split into two free blocks at a random base chosen
+    /// by KASLR. Since the areas are allocated from the end of the first
+    /// fitting block, they grow downwards from the random base.
     fn alloc(&self, size: usize) -> Result<Range<Vaddr>> {
         let mut lock_guard = self.freelist.lock();
         if lock_guard.is_none() {
             let mut freelist: BTreeMap<Vaddr, KVirtAreaFreeNode> = BTreeMap::new();
-            freelist.insert(
-                self.fullrange.start,
-                KVirtAreaFreeNode::new(self.fullrange.clone()),
-            );
+            let base = kaslr::random_base(&self.fullrange);
+            if base != self.fullrange.start {
+                freelist.insert(
+                    self.fullrange.start,
+                    KVirtAreaFreeNode::new(self.fullrange.start..base),
+                );
+            }
+            freelist.insert(base, KVirtAreaFreeNode::new(base..self.fullrange.end));
             *lock_guard = Some(freelist);
         }
         let freelist = lock_guard.as_mut().unwrap();
@@ -169,6 +180,9 @@ pub struct KVirtArea<M: AllocatorSelector + 'static> {
 impl<M: AllocatorSelector + 'static> KVirtArea<M> {
     pub fn new(size: usize) -> Self {
         let allocator = M::select_allocator();
+        if crate::arch::irq::is_local_enabled() {
+            Self::drop_deferred();
+        }
         let range = allocator.alloc(size).unwrap();
         Self {
             range,
@@ -192,6 +206,19 @@ impl<M: AllocatorSelector + 'static> KVirtArea<M> {
         self.range.len()
     }
 
+    /// Drops the areas whose drops are deferred because IRQs were disabled.
+    ///
+    /// This must be called with IRQs enabled.
+    fn drop_deferred() {
+        let deferred = core::mem::take(&mut *M::select_allocator().deferred.lock());
+        for range in deferred {
+            drop(Self {
+                range,
+                phantom: PhantomData,
+            });
+        }
+    }
+
     fn query_page(&self, addr: Vaddr) -> PageTableItem {
         assert!(self.start() <= addr && self.end() >= addr);
         let start = addr.align_down(PAGE_SIZE);
@@ -310,6 +337,14 @@ impl KVirtArea<Untracked> {
 
 impl<M: AllocatorSelector + 'static> Drop for KVirtArea<M> {
     fn drop(&mut self) {
+        // Unmapping the pages waits for the TLB flushes on other CPUs, which must not be done
+        // with IRQs disabled (e.g., when a kernel stack is dropped during a context switch). So
+        // the area is kept mapped until the next area is created with IRQs enabled.
+        if !crate::arch::irq::is_local_enabled() {
+            M::select_allocator().deferred.lock().push(self.range());
+            return;
+        }
+
         // 1. unmap all mapped pages.
         let page_table = KERNEL_PAGE_TABLE.get().unwrap();
         let range = self.start()..self.end();
@@ -38,6 +38,7 @@
 //! If the address width is (according to [`crate::arch::mm::PagingConsts`])
 //! 39 bits or 57 bits, the memory space just adjust proportionally.
 
+pub mod kaslr;
 pub(crate) mod kvirt_area;
 
 use core::ops::Range;
@@ -74,17 +75,18 @@ pub const KERNEL_END_VADDR: Vaddr = 0xffff_ffff_ffff_0000 << ADDR_WIDTH_SHIFT;
 
 /// The kernel code is linear mapped to this address.
 ///
-/// FIXME: This offset should be randomly chosen by the loader or the
-/// boot compatibility layer. But we disabled it because OSTD
-/// doesn't support relocatable kernel yet.
+/// The address is randomly chosen by the boot code on x86-64, see
+/// [`kaslr::kaslr_offset`].
 pub fn kernel_loaded_offset() -> usize {
-    KERNEL_CODE_BASE_VADDR
+    KERNEL_CODE_BASE_VADDR + kaslr::kaslr_offset()
 }
 
 #[cfg(target_arch = "x86_64")]
 const KERNEL_CODE_BASE_VADDR: usize = 0xffff_ffff_8000_0000 << ADDR_WIDTH_SHIFT;
 #[cfg(target_arch = "riscv64")]
 const KERNEL_CODE_BASE_VADDR: usize = 0xffff_ffff_0000_0000 << ADDR_WIDTH_SHIFT;
+#[cfg(target_arch = "aarch64")]
+const KERNEL_CODE_BASE_VADDR: usize = 0xffff_ffff_0000_0000 << ADDR_WIDTH_SHIFT;
 
 const FRAME_METADATA_CAP_VADDR: Vaddr = 0xffff_e100_0000_0000 << ADDR_WIDTH_SHIFT;
 const FRAME_METADATA_BASE_VADDR: Vaddr = 0xffff_e000_0000_0000 << ADDR_WIDTH_SHIFT;
@@ -134,6 +136,8 @@ pub static KERNEL_PAGE_TABLE: Once<PageTable<KernelMode, PageTableEntry, PagingC
 pub fn init_kernel_page_table(meta_pages: Segment<MetaPageMeta>) {
     info!("Initializing the kernel page table");
 
+    kaslr::init();
+
     let regions = &crate::boot::EARLY_INFO.get().unwrap().memory_regions;
     let phys_mem_cap = regions.iter().map(|r| r.base() + r.len()).max().unwrap();
 
@@ -146,10 +150,40 @@ pub fn init_kernel_page_table(meta_pages: Segment<MetaPageMeta>) {
         kpt.make_shared_tables(pte_index_max / 2..pte_index_max);
     }
 
+    // On AArch64, the physical memory below the RAM holds the MMIO registers of the
+    // devices, some of which (e.g., the interrupt controller) are accessed with the
+    // linear mapping. They must be mapped as device memory, since the speculative
+    // accesses to normal memory may have side effects on the devices.
+    #[cfg(target_arch = "aarch64")]
+    let ram_start = regions
+        .iter()
+        .filter(|r| r.typ() == MemoryRegionType::Usable)
+        .map(|r| r.base())
+        .min()
+        .unwrap()
+        .align_down(PAGE_SIZE);
+    #[cfg(not(target_arch = "aarch64"))]
+    let ram_start = 0;
+
+    // Do linear mappings for the device memory below the RAM.
+    if ram_start > 0 {
+        let from = LINEAR_MAPPING_BASE_VADDR..LINEAR_MAPPING_BASE_VADDR + ram_start;
+        let to = 0..ram_start;
+        let prop = PageProperty {
+            flags: PageFlags::RW,
+            cache: CachePolicy::Uncacheable,
+            priv_flags: PrivilegedPageFlags::GLOBAL,
+        };
+        // SAFETY: we are doing I/O mappings for the kernel.
+        unsafe {
+            kpt.map(&from, &to, prop).unwrap();
+        }
+    }
+
     // Do linear mappings for the kernel.
     {
-        let from = LINEAR_MAPPING_BASE_VADDR..LINEAR_MAPPING_BASE_VADDR + phys_mem_cap;
-        let to = 0..phys_mem_cap;
+        let from = LINEAR_MAPPING_BASE_VADDR + ram_start..LINEAR_MAPPING_BASE_VADDR + phys_mem_cap;
+        let to = ram_start..phys_mem_cap;
         let prop = PageProperty {
             flags: PageFlags::RW,
             cache: CachePolicy::Writeback,
@@ -12,7 +12,10 @@ pub(crate) mod dma;
 pub mod frame;
 pub(crate) mod heap_allocator;
 mod io;
+#[cfg(feature = "kasan")]
+pub(crate) mod kasan;
 pub(crate) mod kspace;
+pub mod numa;
 mod offset;
 pub(crate) mod page_prop;
 pub(crate) mod page_table;
@@ -26,6 +29,7 @@ pub use self::{
     dma::{Daddr, DmaCoherent, DmaDirection, DmaStream, DmaStreamSlice, HasDaddr},
     frame::{
         allocator::FrameAllocOptions,
+        max_paddr,
         segment::{Segment, USegment},
         untyped::{AnyUFrameMeta, UFrame, UntypedMem},
         Frame,
@@ -34,6 +38,7 @@ pub use self::{
         Fallible, FallibleVmRead, FallibleVmWrite, Infallible, PodOnce, VmIo, VmIoOnce, VmReader,
         VmWriter,
     },
+    kspace::kaslr::kaslr_offset,
     page_prop::{CachePolicy, PageFlags, PageProperty},
     vm_space::VmSpace,
 };
@@ -43,6 +48,16 @@ pub(crate) use self::{
 };
 use crate::arch::mm::PagingConsts;
 
+/// Makes the instruction caches of all CPUs coherent with the prior writes
+/// to memory.
+///
+/// This should be called before executing the code that has been written as
+/// data, e.g., when a user mapping becomes executable. The stale TLB entries
+/// are not flushed by this function.
+pub fn sync_icache_all() {
+    crate::arch::mm::sync_icache_all();
+}
+
 /// The level of a page table node or a frame.
 pub type PagingLevel = u8;
 
@@ -0,0 +1,231 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! Non-uniform memory access (NUMA).
+//!
+//! The CPUs and the physical memory are grouped into NUMA nodes. Accessing the memory in the
+//! node of the current CPU is faster than accessing the memory in other nodes, so the frame
+//! allocator keeps separate free lists for each node and prefers the node of the current CPU.
+//!
+//! The NUMA nodes are numbered from zero. If the platform does not report its NUMA topology,
+//! all CPUs and memory belong to node zero.
+
+use alloc::vec;
+use core::ops::Range;
+
+use spin::Once;
+
+use crate::{
+    cpu::{CpuId, PinCurrentCpu},
+    mm::Paddr,
+    prelude::*,
+};
+
+/// The maximum number of NUMA nodes.
+pub const MAX_NODES: usize = 64;
+
+/// A set of NUMA nodes.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct NodeSet {
+    bits: u64,
+}
+
+impl NodeSet {
+    /// Creates a new `NodeSet` with all NUMA nodes in the system.
+    pub fn new_full() -> Self {
+        let num_nodes = num_nodes();
+        if num_nodes == MAX_NODES {
+            Self { bits: !0 }
+        } else {
+            Self {
+                bits: (1 << num_nodes) - 1,
+            }
+        }
+    }
+
+    /// Creates a new `NodeSet` with no NUMA nodes.
+    pub const fn new_empty() -> Self {
+        Self { bits: 0 }
+    }
+
+    /// Creates a new `NodeSet` from a bitmap, where bit `i` represents node `i`.
+    pub const fn from_bits(bits: u64) -> Self {
+        Self { bits }
+    }
+
+    /// Returns the bitmap of the set, where bit `i` represents node `i`.
+    pub const fn bits(&self) -> u64 {
+        self.bits
+    }
+
+    /// Adds a node to the set.
+    ///
+    /// # Panics
+    ///
+    /// This method panics if `node` is not less than [`MAX_NODES`].
+    pub fn add(&mut self, node: usize) {
+        assert!(node < MAX_NODES);
+        self.bits |= 1 << node;
+    }
+
+    /// Returns true if the set contains the specified node.
+    pub fn contains(&self, node: usize) -> bool {
+        node < MAX_NODES && self.bits & (1 << node) != 0
+    }
+
+    /// Returns the intersection of the two sets.
+    pub fn intersection(&self, other: &Self) -> Self {
+        Self {
+            bits: self.bits & other.bits,
+        }
+    }
+
+    /// Returns the number of nodes in the set.
+    pub fn count(&self) -> usize {
+        self.bits.count_ones() as usize
+    }
+
+    /// Returns true if the set is empty.
+    pub fn is_empty(&self) -> bool {
+        self.bits == 0
+    }
+
+    /// Iterates over the nodes in the set in the ascending order.
+    pub fn iter(&self) -> impl Iterator<Item = usize> + '_ {
+        let bits = self.bits;
+        (0..MAX_NODES).filter(move |node| bits & (1 << node) != 0)
+    }
+}
+
+/// The NUMA topology reported by the platform.
+pub(crate) struct NumaTopology {
+    /// The physical memory ranges and the platform-specific IDs of the nodes they belong to.
+    pub(crate) memory_ranges: Vec<(Range<Paddr>, u32)>,
+    /// The CPU IDs and the platform-specific IDs of the nodes they belong to.
+    pub(crate) cpus: Vec<(u32, u32)>,
+}
+
+struct NumaInfo {
+    num_nodes: usize,
+    /// The physical memory ranges and the nodes they belong to.
+    memory_ranges: Vec<(Range<Paddr>, usize)>,
+    /// The nodes of the CPUs, indexed by the CPU IDs.
+    cpu_nodes: Vec<usize>,
+}
+
+static NUMA_INFO: Once<NumaInfo> = Once::new();
+
+/// Initializes the NUMA topology.
+///
+/// This function must be called before the frame allocator is initialized.
+pub(crate) fn init() {
+    let topology = crate::arch::numa_topology();
+
+    NUMA_INFO.call_once(|| {
+        let Some(topology) = topology else {
+            return NumaInfo {
+                num_nodes: 1,
+                memory_ranges: Vec::new(),
+                cpu_nodes: Vec::new(),
+            };
+        };
+
+        // Map the platform-specific IDs to dense node IDs, keeping their order.
+        let mut platform_ids: Vec<u32> = topology
+            .memory_ranges
+            .iter()
+            .map(|(_, id)| *id)
+            .chain(topology.cpus.iter().map(|(_, id)| *id))
+            .collect();
+        platform_ids.sort_unstable();
+        platform_ids.dedup();
+        if platform_ids.len() > MAX_NODES {
+            log::warn!(
+                "Found {} NUMA nodes, but only {} are supported",
+                platform_ids.len(),
+                MAX_NODES
+            );
+        }
+        let node_of = |id: u32| platform_ids.binary_search(&id).unwrap().min(MAX_NODES - 1);
+
+        let memory_ranges = topology
+            .memory_ranges
+            .iter()
+            .map(|(range, id)| (range.clone(), node_of(*id)))
+            .collect();
+
+        let mut cpu_nodes = Vec::new();
+        for (cpu, id) in topology.cpus.iter() {
+            let index = *cpu as usize;
+            if index >= cpu_nodes.len() {
+                cpu_nodes.resize(index + 1, 0);
+            }
+            cpu_nodes[index] = node_of(*id);
+        }
+
+        let num_nodes = platform_ids.len().clamp(1, MAX_NODES);
+        log::info!("Found {} NUMA node(s)", num_nodes);
+
+        NumaInfo {
+            num_nodes,
+            memory_ranges,
+            cpu_nodes,
+        }
+    });
+}
+
+/// Returns the number of NUMA nodes.
+pub fn num_nodes() -> usize {
+    NUMA_INFO.get().map_or(1, |info| info.num_nodes)
+}
+
+/// Returns the NUMA node of the CPU.
+pub fn node_of_cpu(cpu: CpuId) -> usize {
+    NUMA_INFO
+        .get()
+        .and_then(|info| info.cpu_nodes.get(cpu.as_usize()).copied())
+        .unwrap_or(0)
+}
+
+/// Returns the NUMA node of the physical address.
+pub fn node_of_paddr(paddr: Paddr) -> usize {
+    NUMA_INFO
+        .get()
+        .and_then(|info| {
+            info.memory_ranges
+                .iter()
+                .find(|(range, _)| range.contains(&paddr))
+                .map(|(_, node)| *node)
+        })
+        .unwrap_or(0)
+}
+
+/// Returns the NUMA node of the current CPU.
+///
+/// This function returns `None` if the current CPU has not been initialized.
+pub(crate) fn current_node(guard: &dyn PinCurrentCpu) -> Option<usize> {
+    crate::cpu::try_current_cpu(guard).map(node_of_cpu)
+}
+
+/// Splits the physical address range into subranges that belong to different NUMA nodes.
+///
+/// The parts that do not belong to any reported memory ranges are assigned to node zero.
+pub(crate) fn split_by_node(range: Range<Paddr>) -> Vec<(Range<Paddr>, usize)> {
+    let mut boundaries = vec![range.start, range.end];
+    let memory_ranges = NUMA_INFO
+        .get()
+        .map_or(&[][..], |info| &info.memory_ranges[..]);
+    for (node_range, _) in memory_ranges.iter() {
+        for addr in [node_range.start, node_range.end] {
+            if range.start < addr && addr < range.end {
+                boundaries.push(addr);
+            }
+        }
+    }
+    boundaries.sort_unstable();
+    boundaries.dedup();
+
+    boundaries
+        .windows(2)
+        .map(|window| (window[0]..window[1], node_of_paddr(window[0])))
+        .collect()
+}
@@ -71,8 +71,8 @@ use align_ext::AlignExt;
 
 use super::{
     page_size, pte_index, Child, Entry, KernelMode, MapTrackingStatus, PageTable,
-    PageTableEntryTrait, PageTableError, PageTableMode, PageTableNode, PagingConstsTrait,
-    PagingLevel, RawPageTableNode, UserMode,
+    PageTableEntryTrait, PageTableError, PageTableMode, PageTableNode, PageTablePageMeta,
+    PagingConstsTrait, PagingLevel, RawPageTableNode, UserMode,
 };
 use crate::{
     mm::{
@@ -173,12 +173,18 @@ where
         // Go down and get proper locks. The cursor should hold a lock of a
         // page table node containing the virtual address range.
         //
+        // If the range covers exactly one entry of the node, the node is still
+        // the one to be locked, so that the entry can be replaced as a whole,
+        // e.g., when a huge page is mapped in place of a child page table.
+        //
         // While going down, previous guards of too-high levels will be released.
         loop {
             let start_idx = pte_index::<C>(va.start, cursor.level);
             let level_too_high = {
                 let end_idx = pte_index::<C>(va.end - 1, cursor.level);
-                cursor.level > 1 && start_idx == end_idx
+                let covers_entry = va.start % page_size::<C>(cursor.level) == 0
+                    && va.len() == page_size::<C>(cursor.level);
+                cursor.level > 1 && start_idx == end_idx && !covers_entry
             };
             if !level_too_high {
                 break;
@@ -404,14 +410,21 @@ where
 
     /// Maps the range starting from the current address to a [`Frame<dyn AnyFrameMeta>`].
     ///
-    /// It returns the previously mapped [`Frame<dyn AnyFrameMeta>`] if that exists.
+    /// The frame can be a huge frame, which will be mapped by a PTE at the
+    /// level of the frame. If the PTE points to an empty child page table,
+    /// the child page table is replaced.
+    ///
+    /// It returns the previously mapped [`Frame<dyn AnyFrameMeta>`] if that
+    /// exists, or the replaced child page table. Either of them should be
+    /// dropped only after the TLB entries are flushed.
     ///
     /// # Panics
     ///
     /// This function will panic if
     ///  - the virtual address range to be mapped is out of the range;
     ///  - the alignment of the page is not satisfied by the virtual address;
-    ///  - it is already mapped to a huge page while the caller wants to map a smaller one.
+    ///  - it is already mapped to a huge page while the caller wants to map a smaller one;
+    ///  - there are smaller pages mapped while the caller wants to map a huge one.
     ///
     /// # Safety
     ///
@@ -425,6 +438,12 @@ where
         let end = self.0.va + page.size();
         assert!(end <= self.0.barrier_va.end);
 
+        // Go up if the cursor stays at a lower level than the page, e.g., after jumping.
+        while self.0.level < page.level() {
+            debug_assert!(self.0.level < self.0.guard_level);
+            self.0.pop_level();
+        }
+
         // Go down if not applicable.
         while self.0.level > C::HIGHEST_TRANSLATION_LEVEL
             || self.0.va % page_size::<C>(self.0.level) != 0
@@ -461,8 +480,15 @@ where
         match old {
             Child::Frame(old_page, _) => Some(old_page),
             Child::None => None,
-            Child::PageTable(_) => {
-                todo!("Dropping page table nodes while mapping requires TLB flush")
+            Child::PageTable(pt) => {
+                let pt = pt.lock();
+                assert_eq!(
+                    pt.nr_children(),
+                    0,
+                    "Mapping a huge page over already mapped smaller pages"
+                );
+                let pt: Frame<PageTablePageMeta<E, C>> = pt.into_raw().into();
+                Some(pt.into())
             }
             Child::Untracked(_, _, _) => panic!("Mapping a tracked page in an untracked range"),
         }
@@ -638,14 +664,14 @@ where
 
             return match old {
                 Child::Frame(page, prop) => PageTableItem::Mapped {
-                    va: self.0.va,
+                    va: cur_va,
                     page,
                     prop,
                 },
                 Child::Untracked(pa, level, prop) => {
-                    debug_assert_eq!(level, self.0.level);
+                    debug_assert_eq!(level, cur_level);
                     PageTableItem::MappedUntracked {
-                        va: self.0.va,
+                        va: cur_va,
                         pa,
                         len: page_size::<C>(level),
                         prop,
@@ -7,7 +7,7 @@ use crate::{
     mm::{
         kspace::LINEAR_MAPPING_BASE_VADDR,
         page_prop::{CachePolicy, PageFlags},
-        FrameAllocOptions, MAX_USERSPACE_VADDR,
+        Frame, FrameAllocOptions, MAX_USERSPACE_VADDR,
     },
     prelude::*,
 };
@@ -42,6 +42,46 @@ fn test_tracked_map_unmap() {
     assert!(pt.query(from.start + 10).is_none());
 }
 
+#[ktest]
+fn test_tracked_huge_map_unmap() {
+    let pt = PageTable::<UserMode>::empty();
+    let huge_size = page_size::<PagingConsts>(2);
+
+    // Leave an empty child page table where the huge page will be mapped.
+    let small = huge_size..huge_size + PAGE_SIZE;
+    let page = FrameAllocOptions::new().alloc_frame().unwrap();
+    let prop = PageProperty::new(PageFlags::RW, CachePolicy::Writeback);
+    unsafe { pt.cursor_mut(&small).unwrap().map(page.into(), prop) };
+    let taken = unsafe { pt.cursor_mut(&small).unwrap().take_next(small.len()) };
+    assert!(matches!(taken, PageTableItem::Mapped { va, .. } if va == small.start));
+
+    let from = huge_size..huge_size * 2;
+    let page = FrameAllocOptions::new().alloc_huge_frame(2).unwrap();
+    assert_eq!(page.size(), huge_size);
+    let start_paddr = page.start_paddr();
+    let old = unsafe { pt.cursor_mut(&from).unwrap().map(page.into(), prop) };
+    assert!(old.is_some_and(|old| old.level() == 1));
+    assert_eq!(
+        pt.query(from.start + PAGE_SIZE + 10).unwrap().0,
+        start_paddr + PAGE_SIZE + 10
+    );
+
+    let taken = unsafe { pt.cursor_mut(&from).unwrap().take_next(from.len()) };
+    let PageTableItem::Mapped { va, page, .. } = taken else {
+        panic!("the huge page is not mapped");
+    };
+    assert_eq!(va, from.start);
+    assert_eq!(page.size(), huge_size);
+    assert!(pt.query(from.start + 10).is_none());
+
+    // The unmapped huge frame can be split into base frames.
+    let page: Frame<()> = page.try_into().unwrap();
+    let frames = page.split(|_| ()).unwrap();
+    assert_eq!(frames.start_paddr(), start_paddr);
+    assert_eq!(frames.size(), huge_size);
+    assert!(frames.into_iter().all(|frame| frame.level() == 1));
+}
+
 #[ktest]
 fn test_untracked_map_unmap() {
     let pt = PageTable::<KernelMode>::empty();
@@ -3,21 +3,22 @@
 //! TLB flush operations.
 
 use alloc::vec::Vec;
-use core::ops::Range;
+use core::{cell::RefCell, ops::Range};
 
 use super::{
     frame::{meta::AnyFrameMeta, Frame},
     Vaddr, PAGE_SIZE,
 };
-use crate::{
-    cpu::{CpuSet, PinCurrentCpu},
-    cpu_local,
-    sync::{LocalIrqDisabled, SpinLock},
-    task::disable_preempt,
-};
+use crate::cpu::{CpuSet, PinCurrentCpu};
 
 /// A TLB flusher that is aware of which CPUs are needed to be flushed.
 ///
+/// The flush requests to other CPUs are deferred and batched. They are kept
+/// in the flusher, which is dedicated to a cursor of an address space, until
+/// [`Self::dispatch_tlb_flush`] is called or the flusher is dropped. Then all
+/// the relevant CPUs are notified with one inter-processor call, and the
+/// dispatching CPU waits until they have all performed the requests.
+///
 /// The flusher needs to stick to the current CPU.
 pub struct TlbFlusher<G: PinCurrentCpu> {
     target_cpus: CpuSet,
@@ -25,6 +26,12 @@ pub struct TlbFlusher<G: PinCurrentCpu> {
     // list brings non-trivial overhead.
     need_remote_flush: bool,
     need_self_flush: bool,
+    /// The pending requests that have not been dispatched.
+    ops: RefCell<OpsStack>,
+    /// The pages that can only be dropped after the pending requests are dispatched.
+    //
+    // Lock ordering: borrow `ops` before `defer_pages`.
+    defer_pages: RefCell<Vec<Frame<dyn AnyFrameMeta>>>,
     _pin_current: G,
 }
 
@@ -50,13 +57,15 @@ impl<G: PinCurrentCpu> TlbFlusher<G> {
             target_cpus,
             need_remote_flush,
             need_self_flush,
+            ops: RefCell::new(OpsStack::new()),
+            defer_pages: RefCell::new(Vec::new()),
             _pin_current: pin_current_guard,
         }
     }
 
     /// Issues a pending TLB flush request.
     ///
-    /// On SMP systems, the notification is sent to all the relevant CPUs only
+    /// On SMP systems, the request is performed on all the relevant CPUs only
     /// when [`Self::dispatch_tlb_flush`] is called.
     pub fn issue_tlb_flush(&self, op: TlbFlushOp) {
         self.issue_tlb_flush_(op, None);
@@ -64,13 +73,27 @@ impl<G: PinCurrentCpu> TlbFlusher<G> {
 
     /// Dispatches all the pending TLB flush requests.
     ///
-    /// The pending requests are issued by [`Self::issue_tlb_flush`].
+    /// The pending requests are issued by [`Self::issue_tlb_flush`]. This
+    /// method returns after the requests are performed on all the relevant
+    /// CPUs, and then the pages kept for the requests are dropped.
+    ///
+    /// If there are requests for other CPUs, this method must be called with
+    /// IRQs enabled, i.e., without holding any lock that disables IRQs. See
+    /// [`crate::smp::inter_processor_call_sync`] for the reason. The same
+    /// applies to dropping the flusher, which dispatches the requests.
     pub fn dispatch_tlb_flush(&self) {
         if !self.need_remote_flush {
             return;
         }
 
-        crate::smp::inter_processor_call(&self.target_cpus, do_remote_flush);
+        let ops = core::mem::replace(&mut *self.ops.borrow_mut(), OpsStack::new());
+        if ops.is_empty() {
+            return;
+        }
+
+        crate::smp::inter_processor_call_sync(&self.target_cpus, &|| ops.perform_on_current());
+
+        self.defer_pages.borrow_mut().clear();
     }
 
     /// Issues a TLB flush request that must happen before dropping the page.
@@ -106,16 +129,20 @@ impl<G: PinCurrentCpu> TlbFlusher<G> {
         }
 
         // Slow path for multi-CPU cases.
-        for cpu in self.target_cpus.iter() {
-            let mut op_queue = FLUSH_OPS.get_on_cpu(cpu).lock();
-            if let Some(drop_after_flush) = drop_after_flush.clone() {
-                PAGE_KEEPER.get_on_cpu(cpu).lock().push(drop_after_flush);
-            }
-            op_queue.push(op.clone());
+        self.ops.borrow_mut().push(op);
+        if let Some(drop_after_flush) = drop_after_flush {
+            self.defer_pages.borrow_mut().push(drop_after_flush);
         }
     }
 }
 
+impl<G: PinCurrentCpu> Drop for TlbFlusher<G> {
+    fn drop(&mut self) {
+        // The pages must not be dropped before the TLB entries are flushed.
+        self.dispatch_tlb_flush();
+    }
+}
+
 /// The operation to flush TLB entries.
 #[derive(Debug, Clone)]
 pub enum TlbFlushOp {
@@ -154,23 +181,6 @@ impl TlbFlushOp {
     }
 }
 
-// The queues of pending requests on each CPU.
-//
-// Lock ordering: lock FLUSH_OPS before PAGE_KEEPER.
-cpu_local! {
-    static FLUSH_OPS: SpinLock<OpsStack, LocalIrqDisabled> = SpinLock::new(OpsStack::new());
-    static PAGE_KEEPER: SpinLock<Vec<Frame<dyn AnyFrameMeta>>, LocalIrqDisabled> = SpinLock::new(Vec::new());
-}
-
-fn do_remote_flush() {
-    let preempt_guard = disable_preempt();
-    let current_cpu = preempt_guard.current_cpu();
-
-    let mut op_queue = FLUSH_OPS.get_on_cpu(current_cpu).lock();
-    op_queue.flush_all();
-    PAGE_KEEPER.get_on_cpu(current_cpu).lock().clear();
-}
-
 /// If a TLB flushing request exceeds this threshold, we flush all.
 pub(crate) const FLUSH_ALL_RANGE_THRESHOLD: usize = 32 * PAGE_SIZE;
 
@@ -194,32 +204,31 @@ impl OpsStack {
         }
     }
 
+    fn is_empty(&self) -> bool {
+        !self.need_flush_all && self.size == 0
+    }
+
     fn push(&mut self, op: TlbFlushOp) {
         if self.need_flush_all {
             return;
         }
 
-        if self.size < FLUSH_ALL_OPS_THRESHOLD {
-            self.ops[self.size] = Some(op);
-            self.size += 1;
-        } else {
+        if matches!(op, TlbFlushOp::All) || self.size == FLUSH_ALL_OPS_THRESHOLD {
             self.need_flush_all = true;
             self.size = 0;
+        } else {
+            self.ops[self.size] = Some(op);
+            self.size += 1;
         }
     }
 
-    fn flush_all(&mut self) {
+    fn perform_on_current(&self) {
         if self.need_flush_all {
             crate::arch::mm::tlb_flush_all_excluding_global();
-            self.need_flush_all = false;
         } else {
-            for i in 0..self.size {
-                if let Some(op) = &self.ops[i] {
-                    op.perform_on_current();
-                }
+            for op in self.ops[..self.size].iter().flatten() {
+                op.perform_on_current();
             }
         }
-
-        self.size = 0;
     }
 }
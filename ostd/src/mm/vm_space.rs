@@ -322,7 +322,13 @@ impl CursorMut<'_, '_> {
 
     /// Map a frame into the current slot.
     ///
+    /// The frame can be a huge frame (see [`Frame::level`]), in which case
+    /// the current virtual address must be aligned to the size of the frame.
+    /// A huge frame can only be mapped where there are no mapped pages.
+    ///
     /// This method will bring the cursor to the next slot after the modification.
+    ///
+    /// [`Frame::level`]: crate::mm::Frame::level
     pub fn map(&mut self, frame: UFrame, prop: PageProperty) {
         let start_va = self.virt_addr();
         // SAFETY: It is safe to map untyped memory into the userspace.
@@ -475,6 +481,8 @@ pub enum VmItem {
         /// The virtual address of the slot.
         va: Vaddr,
         /// The mapped frame.
+        ///
+        /// It may be a huge frame, which covers more than one base page.
         frame: UFrame,
         /// The property of the slot.
         prop: PageProperty,
@@ -2,10 +2,17 @@
 
 //! Panic support.
 
-use core::ffi::c_void;
+pub mod pstore;
+mod symbols;
+
+use core::{
+    ffi::c_void,
+    fmt::{self, Write},
+};
 
 pub use unwinding::panic::{begin_panic, catch_unwind};
 
+pub use self::symbols::lookup_symbol;
 use crate::{
     arch::qemu::{exit_qemu, QemuExitCode},
     early_print, early_println,
@@ -43,7 +50,11 @@ pub fn __ostd_panic_handler(info: &core::panic::PanicInfo) -> ! {
 
     early_println!("Non-resettable panic! {:#?}", info);
 
+    #[cfg(target_arch = "x86_64")]
+    crate::arch::kgdb::breakpoint();
+
     print_stack_trace();
+    save_panic_report(info);
     abort();
 }
 
@@ -62,22 +73,57 @@ pub fn print_stack_trace() {
     let _lock = BACKTRACE_PRINT_LOCK.lock();
 
     early_println!("Printing stack trace:");
+    write_stack_trace(&mut EarlyConsole);
+}
 
-    struct CallbackData {
+/// Saves the report of a panic to the persistent storage, if there is one.
+///
+/// The report contains the panic information, the stack trace of the current thread if
+/// `info.can_unwind()`, and the recent log messages. See [`pstore`] for how the report
+/// is persisted and read after a reboot.
+pub fn save_panic_report(info: &core::panic::PanicInfo) {
+    pstore::save_report(|w| {
+        let _ = writeln!(w, "{}", info);
+        if info.can_unwind() {
+            let _ = writeln!(w, "\nStack trace:");
+            write_stack_trace(w);
+        }
+        let _ = writeln!(w, "\nRecent logs:");
+        crate::logger::read_recent_logs(|logs| {
+            for chunk in logs.utf8_chunks() {
+                let _ = w.write_str(chunk.valid());
+            }
+        });
+    });
+}
+
+/// Writes the stack trace of the current thread.
+///
+/// The functions in the stack trace are symbolized with the kernel symbol table if it is
+/// available.
+fn write_stack_trace(w: &mut dyn Write) {
+    struct CallbackData<'a> {
         counter: usize,
+        writer: &'a mut dyn Write,
     }
     extern "C" fn callback(unwind_ctx: &UnwindContext<'_>, arg: *mut c_void) -> UnwindReasonCode {
         let data = unsafe { &mut *(arg as *mut CallbackData) };
+        let w = &mut *data.writer;
         data.counter += 1;
         let pc = _Unwind_GetIP(unwind_ctx);
         if pc > 0 {
             let fde_initial_address = _Unwind_FindEnclosingFunction(pc as *mut c_void) as usize;
-            early_println!(
-                "{:4}: fn {:#18x} - pc {:#18x} / registers:",
-                data.counter,
-                fde_initial_address,
-                pc,
+            let _ = write!(
+                w,
+                "{:4}: fn {:#18x} - pc {:#18x}",
+                data.counter, fde_initial_address, pc,
             );
+            // The PC is the return address except for the first frame. Look up the address of
+            // the call instruction, in case that the call is the last instruction of a function.
+            if let Some((name, offset)) = symbols::lookup_symbol(pc - 1) {
+                let _ = write!(w, " <{}+{:#x}>", name, offset + 1);
+            }
+            let _ = writeln!(w, " / registers:");
         }
         // Print the first 8 general registers for any architecture. The register number follows
         // the DWARF standard.
@@ -95,14 +141,27 @@ pub fn print_stack_trace() {
                 }
             }
             if i % 4 == 0 {
-                early_print!("\n    ");
+                let _ = write!(w, "\n    ");
             }
-            early_print!(" {} {:#18x};", reg_name, reg_i);
+            let _ = write!(w, " {} {:#18x};", reg_name, reg_i);
         }
-        early_print!("\n\n");
+        let _ = write!(w, "\n\n");
         UnwindReasonCode::NO_REASON
     }
 
-    let mut data = CallbackData { counter: 0 };
+    let mut data = CallbackData {
+        counter: 0,
+        writer: w,
+    };
     _Unwind_Backtrace(callback, &mut data as *mut _ as _);
 }
+
+/// A writer to the console.
+struct EarlyConsole;
+
+impl Write for EarlyConsole {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        early_print!("{}", s);
+        Ok(())
+    }
+}
@@ -0,0 +1,183 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! The persistent storage of the panic reports (pstore).
+//!
+//! A region of the physical memory can be reserved for the panic reports with the
+//! `ostd.pstore=SIZE@ADDR` kernel command line argument, e.g., `ostd.pstore=0x100000@0x7f00000`.
+//! The region is excluded from the frame allocator, and the report of a panic is saved there.
+//! Since the memory is not cleared by a warm reboot, the report of the panic in the previous
+//! boot can be read after the kernel reboots, until it is erased.
+//!
+//! This is similar to the RAM backend of the pstore (ramoops) in Linux.
+
+use alloc::vec::Vec;
+use core::{
+    fmt,
+    mem::size_of,
+    sync::atomic::{AtomicBool, Ordering},
+};
+
+use spin::Once;
+
+use crate::{
+    boot::{
+        memory_region::{MemoryRegion, MemoryRegionType},
+        EARLY_INFO,
+    },
+    mm::{paddr_to_vaddr, PAGE_SIZE},
+    sync::{LocalIrqDisabled, SpinLock},
+};
+
+const MAGIC: u64 = u64::from_le_bytes(*b"ASTPSTOR");
+
+/// The header at the start of the region, which is followed by the report.
+#[repr(C)]
+struct Header {
+    magic: u64,
+    len: u64,
+}
+
+static REGION: Once<Option<MemoryRegion>> = Once::new();
+
+static IS_READY: AtomicBool = AtomicBool::new(false);
+
+/// Serializes the panics on different CPUs that save their reports.
+static SAVE_LOCK: SpinLock<(), LocalIrqDisabled> = SpinLock::new(());
+
+static PREVIOUS_REPORT: SpinLock<Option<Vec<u8>>> = SpinLock::new(None);
+
+/// Returns the region reserved for the panic reports, if there is one.
+pub(crate) fn reserved_region() -> Option<MemoryRegion> {
+    *REGION.call_once(parse_region)
+}
+
+fn parse_region() -> Option<MemoryRegion> {
+    let early_info = EARLY_INFO.get().unwrap();
+    let value = early_info
+        .kernel_cmdline
+        .split(' ')
+        .find(|arg| arg.starts_with("ostd.pstore="))
+        .map(|arg| arg.split('=').last().unwrap_or_default())?;
+
+    let parsed = value
+        .split_once('@')
+        .and_then(|(size, addr)| Some((parse_usize(size)?, parse_usize(addr)?)));
+    let Some((size, addr)) = parsed else {
+        log::warn!("pstore: invalid region `{}`", value);
+        return None;
+    };
+    if addr % PAGE_SIZE != 0 || size % PAGE_SIZE != 0 || size <= size_of::<Header>() {
+        log::warn!(
+            "pstore: the region `{}` is not page-aligned or is too small",
+            value
+        );
+        return None;
+    }
+
+    // The region must be in the RAM, so that it is in the linear mapping.
+    let is_in_ram = early_info.memory_regions.iter().any(|region| {
+        region.typ() == MemoryRegionType::Usable
+            && region.base() <= addr
+            && addr
+                .checked_add(size)
+                .is_some_and(|end| end <= region.base() + region.len())
+    });
+    if !is_in_ram {
+        log::warn!("pstore: the region `{}` is not in the usable memory", value);
+        return None;
+    }
+
+    Some(MemoryRegion::new(addr, size, MemoryRegionType::Reserved))
+}
+
+fn parse_usize(s: &str) -> Option<usize> {
+    match s.strip_prefix("0x") {
+        Some(hex) => usize::from_str_radix(hex, 16).ok(),
+        None => s.parse().ok(),
+    }
+}
+
+/// Reads the report saved in the previous boot.
+///
+/// This function should be called after the kernel page table is activated.
+pub(crate) fn init() {
+    let Some(region) = reserved_region() else {
+        return;
+    };
+    let (header, data) = region_parts(&region);
+
+    // SAFETY: The region is reserved for the panic reports, and is mapped by the linear mapping.
+    let Header { magic, len } = unsafe { header.read_volatile() };
+    if magic == MAGIC && len as usize <= data.len() {
+        *PREVIOUS_REPORT.lock() = Some(data[..len as usize].to_vec());
+    }
+    IS_READY.store(true, Ordering::Release);
+}
+
+/// Returns the panic report saved in the previous boot, if there is one.
+pub fn previous_panic_report() -> Option<Vec<u8>> {
+    PREVIOUS_REPORT.lock().clone()
+}
+
+/// Erases the panic report saved in the previous boot.
+pub fn erase_previous_panic_report() {
+    let mut previous_report = PREVIOUS_REPORT.lock();
+    if previous_report.take().is_none() {
+        return;
+    }
+    let region = reserved_region().unwrap();
+    let (header, _) = region_parts(&region);
+    // SAFETY: The region is reserved for the panic reports, and is mapped by the linear mapping.
+    unsafe { header.write_volatile(Header { magic: 0, len: 0 }) };
+}
+
+/// Saves the report written by the function, which overwrites the saved report.
+pub(super) fn save_report(write_report: impl FnOnce(&mut dyn fmt::Write)) {
+    if !IS_READY.load(Ordering::Acquire) {
+        return;
+    }
+    // Only save the report of the first panic if multiple CPUs panic.
+    let Some(_guard) = SAVE_LOCK.try_lock() else {
+        return;
+    };
+
+    let region = reserved_region().unwrap();
+    let (header, data) = region_parts(&region);
+    // SAFETY: The region is reserved for the panic reports, and is mapped by the linear mapping.
+    unsafe { header.write_volatile(Header { magic: 0, len: 0 }) };
+
+    let mut writer = ReportWriter { data, len: 0 };
+    write_report(&mut writer);
+
+    let len = writer.len as u64;
+    // SAFETY: The region is reserved for the panic reports, and is mapped by the linear mapping.
+    unsafe { header.write_volatile(Header { magic: MAGIC, len }) };
+}
+
+/// Returns the header and the data part of the region.
+fn region_parts(region: &MemoryRegion) -> (*mut Header, &'static mut [u8]) {
+    let base = paddr_to_vaddr(region.base());
+    let data_offset = size_of::<Header>();
+    // SAFETY: The region is reserved for the panic reports, and is mapped by the linear mapping.
+    // The data part is only accessed by `init` before the region is ready, or by `save_report`
+    // with `SAVE_LOCK` held.
+    let data = unsafe {
+        core::slice::from_raw_parts_mut((base + data_offset) as *mut u8, region.len() - data_offset)
+    };
+    (base as *mut Header, data)
+}
+
+/// A writer that truncates the report if it exceeds the region.
+struct ReportWriter {
+    data: &'static mut [u8],
+    len: usize,
+}
+
+impl fmt::Write for ReportWriter {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        let len = s.len().min(self.data.len() - self.len);
+        self.data[self.len..self.len + len].copy_from_slice(&s.as_bytes()[..len]);
+        self.len += len;
+        Ok(())
+    }
+}
@@ -0,0 +1,71 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! The kernel symbol table.
+//!
+//! The table is placed in the `.ksymtab` section, which is reserved by the linker script and
+//! filled by OSDK after the kernel is linked. The table is empty if the kernel is not built by
+//! OSDK or if the symbols do not fit in the section. The layout of the table is described in
+//! `osdk/src/commands/build/ksymtab.rs`.
+
+use core::mem::size_of;
+
+use crate::mm::{kaslr_offset, Vaddr};
+
+const MAGIC: &[u8; 4] = b"KSYM";
+
+#[repr(C)]
+struct RawSymbol {
+    addr: u64,
+    name_offset: u32,
+    name_len: u32,
+}
+
+extern "C" {
+    fn __ksymtab();
+    fn __ksymtab_end();
+}
+
+/// Finds the function that contains the address.
+///
+/// This function returns the name of the function and the offset of the address in the
+/// function, or `None` if the address is not after any function in the symbol table.
+///
+/// The symbol table records the addresses that the kernel is linked at, so the address is
+/// converted to the link-time one before the lookup (see [`kaslr_offset`]).
+pub fn lookup_symbol(addr: Vaddr) -> Option<(&'static str, usize)> {
+    let addr = addr.checked_sub(kaslr_offset())?;
+    let (symbols, names) = symbol_table()?;
+    let index = symbols
+        .partition_point(|symbol| symbol.addr as Vaddr <= addr)
+        .checked_sub(1)?;
+    let symbol = &symbols[index];
+
+    let name_start = symbol.name_offset as usize;
+    let name = names.get(name_start..name_start + symbol.name_len as usize)?;
+    Some((
+        core::str::from_utf8(name).ok()?,
+        addr - symbol.addr as Vaddr,
+    ))
+}
+
+fn symbol_table() -> Option<(&'static [RawSymbol], &'static [u8])> {
+    let start = __ksymtab as usize;
+    let len = __ksymtab_end as usize - start;
+    // SAFETY: The section is in the kernel image, which is always mapped and is never written
+    // after the kernel is linked.
+    let table = unsafe { core::slice::from_raw_parts(start as *const u8, len) };
+
+    let (header, rest) = table.split_at_checked(2 * size_of::<u32>())?;
+    if header[..MAGIC.len()] != MAGIC[..] {
+        return None;
+    }
+    let num_symbols = u32::from_le_bytes(header[MAGIC.len()..].try_into().unwrap()) as usize;
+    let (symbols, names) = rest.split_at_checked(num_symbols * size_of::<RawSymbol>())?;
+
+    // SAFETY: The symbols are placed right after the 8-byte header of the table, which is
+    // aligned to 8 bytes by the linker script. OSDK writes the symbols in the layout of
+    // `RawSymbol` on the little-endian platforms.
+    let symbols =
+        unsafe { core::slice::from_raw_parts(symbols.as_ptr() as *const RawSymbol, num_symbols) };
+    Some((symbols, names))
+}
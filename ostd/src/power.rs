@@ -0,0 +1,55 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! Power management of the machine.
+
+use alloc::{boxed::Box, vec::Vec};
+
+use crate::sync::{LocalIrqDisabled, SpinLock};
+
+/// Turns off the machine.
+///
+/// The caller should have saved the states that should survive, since this function does not
+/// return. If the machine cannot be turned off, the CPU halts forever.
+pub fn poweroff() -> ! {
+    crate::arch::power::poweroff()
+}
+
+/// Restarts the machine.
+///
+/// The caller should have saved the states that should survive, since this function does not
+/// return. If the machine cannot be restarted, the CPU halts forever.
+pub fn restart() -> ! {
+    crate::arch::power::restart()
+}
+
+/// Halts the machine without turning it off.
+///
+/// The interrupts are disabled and the current CPU halts forever.
+pub fn halt() -> ! {
+    crate::arch::power::halt()
+}
+
+type PowerButtonCallback = Box<dyn Fn() + Send + Sync>;
+
+static POWER_BUTTON_CALLBACKS: SpinLock<Vec<PowerButtonCallback>, LocalIrqDisabled> =
+    SpinLock::new(Vec::new());
+
+/// Registers a callback that will be executed when the power button is pressed.
+///
+/// The callback is executed in the interrupt context, so it must not sleep.
+pub fn register_power_button_callback<F>(callback: F)
+where
+    F: Fn() + Send + Sync + 'static,
+{
+    POWER_BUTTON_CALLBACKS.lock().push(Box::new(callback));
+}
+
+/// Handles a press of the power button.
+///
+/// This function is called by the platform-specific code in the interrupt context.
+#[cfg_attr(not(target_arch = "x86_64"), allow(dead_code))]
+pub(crate) fn handle_power_button() {
+    for callback in POWER_BUTTON_CALLBACKS.lock().iter() {
+        callback();
+    }
+}
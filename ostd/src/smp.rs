@@ -6,13 +6,18 @@
 //! processor interrupts.
 
 use alloc::collections::VecDeque;
+use core::{
+    ptr::NonNull,
+    sync::atomic::{AtomicUsize, Ordering},
+};
 
 use spin::Once;
 
 use crate::{
-    cpu::{CpuSet, PinCurrentCpu},
+    cpu::{CpuId, CpuSet, PinCurrentCpu},
     cpu_local,
-    sync::SpinLock,
+    sync::{LocalIrqDisabled, SpinLock},
+    task::disable_preempt,
     trap::{self, IrqLine, TrapFrame},
 };
 
@@ -33,6 +38,78 @@ use crate::{
 pub fn inter_processor_call(targets: &CpuSet, f: fn()) {
     let irq_guard = trap::disable_local();
     let this_cpu_id = irq_guard.current_cpu();
+
+    let call_on_self = queue_calls(targets, this_cpu_id, || CallRequest::Async(f));
+    if call_on_self {
+        // Execute the function synchronously.
+        f();
+    }
+}
+
+/// Executes a function on other processors and waits for the completion.
+///
+/// Unlike [`inter_processor_call`], the function `f` can borrow from the
+/// caller, since this function returns only after `f` has been executed on
+/// all the target processors specified by `targets`, including the current
+/// processor if it is a target. The function should be short and
+/// non-blocking, as it will be executed in interrupt context with interrupts
+/// disabled.
+///
+/// The current processor waits for the completion with IRQs enabled, so that
+/// it keeps executing the functions that other processors request it to
+/// execute meanwhile. Therefore, this function must not be called with IRQs
+/// disabled (except in the bootstrap context), e.g., while holding a lock that
+/// disables IRQs. Otherwise, a target processor that spins on the lock with
+/// IRQs disabled can never execute `f`, and the two processors wait for each
+/// other forever. This rule is checked in debug builds.
+pub fn inter_processor_call_sync(targets: &CpuSet, f: &(dyn Fn() + Sync)) {
+    debug_assert!(
+        crate::arch::irq::is_local_enabled()
+            || crate::IN_BOOTSTRAP_CONTEXT.load(Ordering::Relaxed),
+        "waiting for inter-processor calls with IRQs disabled may deadlock"
+    );
+
+    let preempt_guard = disable_preempt();
+    let this_cpu_id = preempt_guard.current_cpu();
+
+    let nr_remote_targets = targets.iter().filter(|cpu| *cpu != this_cpu_id).count();
+    let call = SyncCall {
+        func: f,
+        nr_pending: AtomicUsize::new(nr_remote_targets),
+    };
+    // SAFETY: The call lives until all the target processors have executed it,
+    // which is waited below.
+    let call_ptr = unsafe { SyncCallPtr::new(&call) };
+
+    {
+        let _irq_guard = trap::disable_local();
+        let call_on_self = queue_calls(targets, this_cpu_id, || CallRequest::Sync(call_ptr));
+        if call_on_self {
+            f();
+        }
+    }
+
+    // The requests from other processors are executed in the IRQ handler
+    // while waiting. They are also executed here in case that the IRQs are
+    // disabled in the bootstrap context.
+    while call.nr_pending.load(Ordering::Acquire) != 0 {
+        {
+            let _irq_guard = trap::disable_local();
+            process_calls(this_cpu_id);
+        }
+        core::hint::spin_loop();
+    }
+}
+
+/// Queues the requests on the target processors other than the current one
+/// and notifies them.
+///
+/// Returns whether the current processor is a target.
+fn queue_calls(
+    targets: &CpuSet,
+    this_cpu_id: CpuId,
+    mut request: impl FnMut() -> CallRequest,
+) -> bool {
     let irq_num = INTER_PROCESSOR_CALL_IRQ.get().unwrap().num();
 
     let mut call_on_self = false;
@@ -41,7 +118,7 @@ pub fn inter_processor_call(targets: &CpuSet, f: fn()) {
             call_on_self = true;
             continue;
         }
-        CALL_QUEUES.get_on_cpu(cpu_id).lock().push_back(f);
+        CALL_QUEUES.get_on_cpu(cpu_id).lock().push_back(request());
     }
     for cpu_id in targets.iter() {
         if cpu_id == this_cpu_id {
@@ -52,31 +129,87 @@ pub fn inter_processor_call(targets: &CpuSet, f: fn()) {
             crate::arch::irq::send_ipi(cpu_id, irq_num);
         }
     }
-    if call_on_self {
-        // Execute the function synchronously.
-        f();
-    }
+
+    call_on_self
 }
 
 static INTER_PROCESSOR_CALL_IRQ: Once<IrqLine> = Once::new();
 
 cpu_local! {
-    static CALL_QUEUES: SpinLock<VecDeque<fn()>> = SpinLock::new(VecDeque::new());
+    static CALL_QUEUES: SpinLock<VecDeque<CallRequest>, LocalIrqDisabled> =
+        SpinLock::new(VecDeque::new());
+}
+
+/// A request to execute a function on a processor.
+enum CallRequest {
+    /// The function is executed without notifying the requester.
+    Async(fn()),
+    /// The function is executed and the requester, which is waiting, is notified.
+    Sync(SyncCallPtr),
+}
+
+/// A function call that the requester waits for.
+struct SyncCall<'a> {
+    func: &'a (dyn Fn() + Sync),
+    /// The number of the target processors that have not executed the function.
+    nr_pending: AtomicUsize,
+}
+
+/// A pointer to a [`SyncCall`] on the stack of the waiting requester.
+#[derive(Clone, Copy)]
+struct SyncCallPtr(NonNull<SyncCall<'static>>);
+
+// SAFETY: The pointed `SyncCall` is `Sync` and lives until all the target
+// processors have executed it.
+unsafe impl Send for SyncCallPtr {}
+
+impl SyncCallPtr {
+    /// Creates a pointer to the call.
+    ///
+    /// # Safety
+    ///
+    /// The call must live until all the target processors have executed it.
+    unsafe fn new(call: &SyncCall<'_>) -> Self {
+        Self(NonNull::from(call).cast())
+    }
+
+    fn execute(self) {
+        // SAFETY: The call is alive until `nr_pending` is decremented.
+        let call = unsafe { self.0.as_ref() };
+        (call.func)();
+        // After this, the call may be gone.
+        call.nr_pending.fetch_sub(1, Ordering::Release);
+    }
 }
 
 fn do_inter_processor_call(_trapframe: &TrapFrame) {
     // TODO: in interrupt context, disabling interrupts is not necessary.
     let preempt_guard = trap::disable_local();
-    let cur_cpu = preempt_guard.current_cpu();
-
-    let mut queue = CALL_QUEUES.get_on_cpu(cur_cpu).lock();
-    while let Some(f) = queue.pop_front() {
-        log::trace!(
-            "Performing inter-processor call to {:#?} on CPU {:#?}",
-            f,
-            cur_cpu
-        );
-        f();
+    process_calls(preempt_guard.current_cpu());
+}
+
+/// Executes the functions that are requested to execute on the current processor.
+///
+/// The local IRQs must be disabled.
+fn process_calls(cur_cpu: CpuId) {
+    let queue = CALL_QUEUES.get_on_cpu(cur_cpu);
+    // The lock is not held while the functions are executed, so that other
+    // processors can queue more requests meanwhile.
+    loop {
+        let Some(request) = queue.lock().pop_front() else {
+            break;
+        };
+        match request {
+            CallRequest::Async(f) => {
+                log::trace!(
+                    "Performing inter-processor call to {:#?} on CPU {:#?}",
+                    f,
+                    cur_cpu
+                );
+                f();
+            }
+            CallRequest::Sync(call) => call.execute(),
+        }
     }
 }
 
@@ -0,0 +1,583 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! A lightweight lock dependency validator.
+//!
+//! When the `lockdep` feature is enabled, every [`SpinLock`] belongs to a _lock
+//! class_, which is identified by the source location where the lock is created.
+//! The validator records the order in which the lock classes are acquired and
+//! reports the following bugs, even if they do not actually cause a deadlock:
+//!
+//! 1. Acquiring a lock that is already held by the current CPU.
+//! 2. Acquiring two lock classes in inconsistent orders, i.e., introducing a
+//!    cycle in the dependency graph of the lock classes.
+//! 3. Acquiring a lock class in the interrupt context and also in the task
+//!    context with local IRQs enabled, e.g., taking a
+//!    `SpinLock<T, PreemptDisabled>` without [`disable_irq`] while the lock is
+//!    also taken by an IRQ handler.
+//! 4. Holding a lock for too long, e.g., busy-waiting for a device while
+//!    holding the lock of the device queue.
+//!
+//! After reporting a bug of the first three kinds, the validator turns itself
+//! off, since the subsequent reports are likely to be caused by the first one.
+//! A long hold is reported once per lock class.
+//!
+//! [`SpinLock`]: super::SpinLock
+//! [`disable_irq`]: super::SpinLock::disable_irq
+
+use core::{
+    cell::RefCell,
+    panic::Location,
+    sync::atomic::{AtomicBool, Ordering},
+};
+
+use crate::{
+    arch, cpu_local,
+    timer::monotonic_ns,
+    trap::{disable_local, in_interrupt_context},
+};
+
+/// The maximum number of the lock classes.
+const MAX_CLASSES: usize = 1024;
+/// The number of slots of the hash table that maps creation sites to classes.
+const NR_CLASS_SLOTS: usize = MAX_CLASSES * 2;
+/// The maximum number of the locks that are held by a CPU at the same time.
+const MAX_HELD_LOCKS: usize = 48;
+/// The time after which holding a lock is considered a bug, in nanoseconds.
+const LONG_HOLD_NS: u64 = 10_000_000;
+
+type ClassId = u16;
+type LockSite = &'static Location<'static>;
+
+static IS_ENABLED: AtomicBool = AtomicBool::new(false);
+
+static GRAPH: spin::Mutex<Graph> = spin::Mutex::new(Graph::new());
+
+cpu_local! {
+    static HELD_LOCKS: RefCell<HeldLocks> = RefCell::new(HeldLocks::new());
+}
+
+/// Enables the validator.
+///
+/// The CPU-local storage must be initialized before calling this function.
+pub(crate) fn init() {
+    IS_ENABLED.store(true, Ordering::Relaxed);
+    log::info!("Lock dependency validator enabled");
+}
+
+/// Records that the current CPU is about to acquire a lock.
+///
+/// The lock is identified by its address, and its class by `class_site`, which
+/// is where the lock is created. The `site` is where the lock is acquired.
+///
+/// If `is_trylock` is true, the lock has already been acquired without waiting,
+/// so no dependencies are recorded for it.
+pub(crate) fn acquire(lock: usize, class_site: LockSite, site: LockSite, is_trylock: bool) {
+    if !IS_ENABLED.load(Ordering::Relaxed) {
+        return;
+    }
+
+    let usage = LockUsage {
+        in_irq: in_interrupt_context(),
+        is_irq_enabled: arch::irq::is_local_enabled(),
+        is_trylock,
+    };
+
+    let irq_guard = disable_local();
+    let mut held_locks = HELD_LOCKS.get_with(&irq_guard).borrow_mut();
+    let mut graph = GRAPH.lock();
+
+    if let Err(bug) = graph.acquire(&mut held_locks, lock, class_site, site, usage) {
+        if turn_off() {
+            bug.report(&graph, &held_locks, class_site, site);
+        }
+    }
+}
+
+/// How a lock is acquired.
+#[derive(Clone, Copy)]
+struct LockUsage {
+    /// Whether the lock is acquired in the interrupt context.
+    in_irq: bool,
+    /// Whether local IRQs are enabled when the lock is acquired.
+    is_irq_enabled: bool,
+    /// Whether the lock has already been acquired without waiting.
+    is_trylock: bool,
+}
+
+/// A bug found by the validator.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Bug {
+    TooManyClasses,
+    RecursiveLocking {
+        held_site: LockSite,
+    },
+    InconsistentIrqUsage {
+        in_irq_site: LockSite,
+        irq_enabled_site: LockSite,
+    },
+    /// Acquiring `class` while holding `held_class` introduces a cycle. The
+    /// path of the reverse dependency is left in the scratch space of the graph.
+    CircularDependency {
+        class: ClassId,
+        held_class: ClassId,
+        held_site: LockSite,
+    },
+    TooManyHeldLocks,
+}
+
+impl Bug {
+    fn report(&self, graph: &Graph, held_locks: &HeldLocks, class_site: LockSite, site: LockSite) {
+        match *self {
+            Bug::TooManyClasses => {
+                log::error!(
+                    "lockdep: too many lock classes ({}), turning off the validator",
+                    MAX_CLASSES
+                );
+            }
+            Bug::RecursiveLocking { held_site } => {
+                log::error!(
+                    "lockdep: recursive locking detected\n\
+                     the lock created at {} is acquired at {}\n\
+                     but it is already held since {}",
+                    class_site,
+                    site,
+                    held_site
+                );
+                held_locks.dump(graph);
+            }
+            Bug::InconsistentIrqUsage {
+                in_irq_site,
+                irq_enabled_site,
+            } => {
+                log::error!(
+                    "lockdep: inconsistent IRQ usage detected\n\
+                     the lock class created at {} is acquired in the interrupt context at {}\n\
+                     but is also acquired with local IRQs enabled at {}",
+                    class_site,
+                    in_irq_site,
+                    irq_enabled_site
+                );
+            }
+            Bug::CircularDependency {
+                class,
+                held_class,
+                held_site,
+            } => {
+                log::error!(
+                    "lockdep: possible circular locking dependency detected\n\
+                     the lock class created at {} is acquired at {}\n\
+                     while holding the lock class created at {}, acquired at {}\n\
+                     but the reverse dependency already exists:",
+                    class_site,
+                    site,
+                    graph.site_of(held_class),
+                    held_site
+                );
+                graph.dump_path(class, held_class);
+                held_locks.dump(graph);
+            }
+            Bug::TooManyHeldLocks => {
+                log::error!(
+                    "lockdep: too many held locks ({}), turning off the validator",
+                    MAX_HELD_LOCKS
+                );
+            }
+        }
+    }
+}
+
+/// Records that the current CPU has released a lock.
+pub(crate) fn release(lock: usize) {
+    if !IS_ENABLED.load(Ordering::Relaxed) {
+        return;
+    }
+
+    let held = {
+        let irq_guard = disable_local();
+        let mut held_locks = HELD_LOCKS.get_with(&irq_guard).borrow_mut();
+        // The lock may be acquired before the validator is enabled.
+        let Some(held) = held_locks.remove(lock) else {
+            return;
+        };
+        held
+    };
+
+    let now = monotonic_ns();
+    if now.saturating_sub(held.acquired_at) < LONG_HOLD_NS {
+        return;
+    }
+
+    let class_site = {
+        let mut graph = GRAPH.lock();
+        let usage = &mut graph.usage[held.class as usize];
+        if usage.is_long_hold_reported {
+            return;
+        }
+        usage.is_long_hold_reported = true;
+        graph.site_of(held.class)
+    };
+    // Do not hold any locks of the validator, since the logger may acquire locks.
+    log::warn!(
+        "lockdep: the lock created at {} was held for {} us since {}",
+        class_site,
+        (now - held.acquired_at) / 1000,
+        held.site
+    );
+}
+
+/// Turns off the validator, returning whether it is turned off by this call.
+fn turn_off() -> bool {
+    IS_ENABLED.swap(false, Ordering::Relaxed)
+}
+
+#[derive(Clone, Copy)]
+struct HeldLock {
+    lock: usize,
+    class: ClassId,
+    /// Where the lock is acquired.
+    site: LockSite,
+    /// When the lock is acquired, in nanoseconds.
+    acquired_at: u64,
+}
+
+/// The locks held by a CPU, in the order of acquisition.
+struct HeldLocks {
+    len: usize,
+    locks: [Option<HeldLock>; MAX_HELD_LOCKS],
+}
+
+impl HeldLocks {
+    const fn new() -> Self {
+        Self {
+            len: 0,
+            locks: [None; MAX_HELD_LOCKS],
+        }
+    }
+
+    fn iter(&self) -> impl Iterator<Item = &HeldLock> {
+        self.locks[..self.len].iter().flatten()
+    }
+
+    fn push(&mut self, held: HeldLock) -> bool {
+        if self.len == MAX_HELD_LOCKS {
+            return false;
+        }
+        self.locks[self.len] = Some(held);
+        self.len += 1;
+        true
+    }
+
+    /// Removes a lock, which is not necessarily the last acquired one.
+    fn remove(&mut self, lock: usize) -> Option<HeldLock> {
+        let index = self.iter().position(|held| held.lock == lock)?;
+        let held = self.locks[index];
+        self.locks.copy_within(index + 1..self.len, index);
+        self.len -= 1;
+        self.locks[self.len] = None;
+        held
+    }
+
+    fn dump(&self, graph: &Graph) {
+        log::error!("locks held by the current CPU:");
+        for held in self.iter() {
+            log::error!(
+                "  lock created at {}, acquired at {}",
+                graph.site_of(held.class),
+                held.site
+            );
+        }
+    }
+}
+
+#[derive(Clone, Copy)]
+struct ClassUsage {
+    /// Where the class is first acquired in the interrupt context.
+    in_irq: Option<LockSite>,
+    /// Where the class is first acquired with local IRQs enabled in the task context.
+    irq_enabled: Option<LockSite>,
+    is_long_hold_reported: bool,
+}
+
+/// The dependency graph of the lock classes.
+///
+/// All the data are statically allocated, since the heap allocator acquires
+/// locks as well.
+struct Graph {
+    nr_classes: usize,
+    /// The creation site of each class.
+    sites: [Option<LockSite>; MAX_CLASSES],
+    /// A hash table with linear probing, whose non-zero entries are class IDs plus one.
+    slots: [u16; NR_CLASS_SLOTS],
+    /// The bit `b` of `after[a]` is set if class `b` is acquired while holding class `a`.
+    after: [[u64; MAX_CLASSES / 64]; MAX_CLASSES],
+    usage: [ClassUsage; MAX_CLASSES],
+    /// The scratch space of the breadth-first search, which records the class from
+    /// which each class is reached, plus one.
+    parents: [u16; MAX_CLASSES],
+    queue: [ClassId; MAX_CLASSES],
+}
+
+impl Graph {
+    const fn new() -> Self {
+        Self {
+            nr_classes: 0,
+            sites: [None; MAX_CLASSES],
+            slots: [0; NR_CLASS_SLOTS],
+            after: [[0; MAX_CLASSES / 64]; MAX_CLASSES],
+            usage: [ClassUsage {
+                in_irq: None,
+                irq_enabled: None,
+                is_long_hold_reported: false,
+            }; MAX_CLASSES],
+            parents: [0; MAX_CLASSES],
+            queue: [0; MAX_CLASSES],
+        }
+    }
+
+    /// Looks up the class created at the site, registering it if needed.
+    fn class_of(&mut self, site: LockSite) -> Option<ClassId> {
+        let hash = (site.line() as usize)
+            .wrapping_mul(31)
+            .wrapping_add(site.column() as usize);
+        let mut slot = hash % NR_CLASS_SLOTS;
+        loop {
+            match self.slots[slot] {
+                0 => break,
+                entry if self.sites[entry as usize - 1] == Some(site) => {
+                    return Some(entry - 1);
+                }
+                _ => slot = (slot + 1) % NR_CLASS_SLOTS,
+            }
+        }
+
+        if self.nr_classes == MAX_CLASSES {
+            return None;
+        }
+        let class = self.nr_classes as ClassId;
+        self.nr_classes += 1;
+        self.sites[class as usize] = Some(site);
+        self.slots[slot] = class + 1;
+        Some(class)
+    }
+
+    /// Records that a lock is about to be acquired, or returns the bug that
+    /// acquiring the lock causes.
+    fn acquire(
+        &mut self,
+        held_locks: &mut HeldLocks,
+        lock: usize,
+        class_site: LockSite,
+        site: LockSite,
+        usage: LockUsage,
+    ) -> Result<(), Bug> {
+        let class = self.class_of(class_site).ok_or(Bug::TooManyClasses)?;
+
+        if let Some(held) = held_locks.iter().find(|held| held.lock == lock) {
+            return Err(Bug::RecursiveLocking {
+                held_site: held.site,
+            });
+        }
+
+        let class_usage = &mut self.usage[class as usize];
+        if usage.in_irq {
+            class_usage.in_irq.get_or_insert(site);
+        } else if usage.is_irq_enabled {
+            class_usage.irq_enabled.get_or_insert(site);
+        }
+        if let (Some(in_irq_site), Some(irq_enabled_site)) =
+            (class_usage.in_irq, class_usage.irq_enabled)
+        {
+            return Err(Bug::InconsistentIrqUsage {
+                in_irq_site,
+                irq_enabled_site,
+            });
+        }
+
+        if !usage.is_trylock {
+            for held in held_locks.iter() {
+                if held.class == class || self.has_edge(held.class, class) {
+                    continue;
+                }
+                if self.find_path(class, held.class) {
+                    return Err(Bug::CircularDependency {
+                        class,
+                        held_class: held.class,
+                        held_site: held.site,
+                    });
+                }
+                self.add_edge(held.class, class);
+            }
+        }
+
+        let held = HeldLock {
+            lock,
+            class,
+            site,
+            acquired_at: monotonic_ns(),
+        };
+        if !held_locks.push(held) {
+            return Err(Bug::TooManyHeldLocks);
+        }
+        Ok(())
+    }
+
+    fn site_of(&self, class: ClassId) -> LockSite {
+        self.sites[class as usize].unwrap()
+    }
+
+    fn has_edge(&self, from: ClassId, to: ClassId) -> bool {
+        self.after[from as usize][to as usize / 64] & (1 << (to % 64)) != 0
+    }
+
+    fn add_edge(&mut self, from: ClassId, to: ClassId) {
+        self.after[from as usize][to as usize / 64] |= 1 << (to % 64);
+    }
+
+    /// Returns whether `to` is reachable from `from`.
+    ///
+    /// If so, the path can be dumped with [`Self::dump_path`].
+    fn find_path(&mut self, from: ClassId, to: ClassId) -> bool {
+        self.parents[..self.nr_classes].fill(0);
+        self.parents[from as usize] = from + 1;
+        self.queue[0] = from;
+        let (mut head, mut tail) = (0, 1);
+
+        while head < tail {
+            let class = self.queue[head];
+            head += 1;
+            for next in 0..self.nr_classes as ClassId {
+                if self.parents[next as usize] != 0 || !self.has_edge(class, next) {
+                    continue;
+                }
+                self.parents[next as usize] = class + 1;
+                if next == to {
+                    return true;
+                }
+                self.queue[tail] = next;
+                tail += 1;
+            }
+        }
+        false
+    }
+
+    fn dump_path(&self, from: ClassId, to: ClassId) {
+        let mut class = to;
+        while class != from {
+            let parent = self.parents[class as usize] - 1;
+            log::error!(
+                "  lock class created at {} is acquired while holding the one created at {}",
+                self.site_of(class),
+                self.site_of(parent)
+            );
+            class = parent;
+        }
+    }
+}
+
+#[cfg(ktest)]
+mod test {
+    use ostd_macros::ktest;
+
+    use super::*;
+
+    /// Returns the caller as a lock site.
+    #[track_caller]
+    fn here() -> LockSite {
+        Location::caller()
+    }
+
+    const TASK_WITH_IRQS: LockUsage = LockUsage {
+        in_irq: false,
+        is_irq_enabled: true,
+        is_trylock: false,
+    };
+
+    const TASK_WITHOUT_IRQS: LockUsage = LockUsage {
+        in_irq: false,
+        is_irq_enabled: false,
+        is_trylock: false,
+    };
+
+    const IRQ: LockUsage = LockUsage {
+        in_irq: true,
+        is_irq_enabled: false,
+        is_trylock: false,
+    };
+
+    // The graphs are too large for the stack.
+    static CYCLE_GRAPH: spin::Mutex<Graph> = spin::Mutex::new(Graph::new());
+    static IRQ_GRAPH: spin::Mutex<Graph> = spin::Mutex::new(Graph::new());
+
+    #[ktest]
+    fn circular_dependency() {
+        let mut graph = CYCLE_GRAPH.lock();
+        let mut held_locks = HeldLocks::new();
+        let (lock_a, class_a) = (0x1000, here());
+        let (lock_b, class_b) = (0x2000, here());
+
+        // A -> B.
+        let site_a = here();
+        assert_eq!(
+            graph.acquire(&mut held_locks, lock_a, class_a, site_a, TASK_WITH_IRQS),
+            Ok(())
+        );
+        assert_eq!(
+            graph.acquire(&mut held_locks, lock_b, class_b, here(), TASK_WITH_IRQS),
+            Ok(())
+        );
+        assert!(held_locks.remove(lock_b).is_some());
+        assert!(held_locks.remove(lock_a).is_some());
+
+        // B -> A.
+        let site_b = here();
+        assert_eq!(
+            graph.acquire(&mut held_locks, lock_b, class_b, site_b, TASK_WITH_IRQS),
+            Ok(())
+        );
+        let bug = graph.acquire(&mut held_locks, lock_a, class_a, here(), TASK_WITH_IRQS);
+        assert!(matches!(
+            bug,
+            Err(Bug::CircularDependency { held_site, .. }) if held_site == site_b
+        ));
+
+        // Acquiring a held lock again is also reported.
+        let bug = graph.acquire(&mut held_locks, lock_b, class_b, here(), TASK_WITH_IRQS);
+        assert_eq!(bug, Err(Bug::RecursiveLocking { held_site: site_b }));
+    }
+
+    #[ktest]
+    fn inconsistent_irq_usage() {
+        let mut graph = IRQ_GRAPH.lock();
+        let mut held_locks = HeldLocks::new();
+        let (lock, class) = (0x1000, here());
+
+        let in_irq_site = here();
+        assert_eq!(
+            graph.acquire(&mut held_locks, lock, class, in_irq_site, IRQ),
+            Ok(())
+        );
+        assert!(held_locks.remove(lock).is_some());
+
+        // It is fine to acquire the lock in the task context with IRQs disabled.
+        assert_eq!(
+            graph.acquire(&mut held_locks, lock, class, here(), TASK_WITHOUT_IRQS),
+            Ok(())
+        );
+        assert!(held_locks.remove(lock).is_some());
+
+        let irq_enabled_site = here();
+        assert_eq!(
+            graph.acquire(
+                &mut held_locks,
+                lock,
+                class,
+                irq_enabled_site,
+                TASK_WITH_IRQS
+            ),
+            Err(Bug::InconsistentIrqUsage {
+                in_irq_site,
+                irq_enabled_site,
+            })
+        );
+    }
+}
@@ -3,21 +3,27 @@
 //! Useful synchronization primitives.
 
 mod guard;
+#[cfg(feature = "lockdep")]
+pub(crate) mod lockdep;
 mod mutex;
-// TODO: refactor this rcu implementation
-// Comment out this module since it raises lint error
-// mod rcu;
+mod percpu_counter;
+mod pi_mutex;
+pub(crate) mod rcu;
 mod rwarc;
 mod rwlock;
 mod rwmutex;
+mod rwsem;
+mod seqlock;
 mod spin;
 mod wait;
 
-// pub use self::rcu::{pass_quiescent_state, OwnerPtr, Rcu, RcuReadGuard, RcuReclaimer};
-pub(crate) use self::guard::GuardTransfer;
+pub(crate) use self::{guard::GuardTransfer, pi_mutex::InheritedPriorities};
 pub use self::{
     guard::{LocalIrqDisabled, PreemptDisabled, WriteIrqDisabled},
     mutex::{ArcMutexGuard, Mutex, MutexGuard},
+    percpu_counter::PerCpuCounter,
+    pi_mutex::{PiMutex, PiMutexGuard},
+    rcu::{call_rcu, synchronize_rcu, OwnerPtr, Rcu, RcuReadGuard},
     rwarc::{RoArc, RwArc},
     rwlock::{
         ArcRwLockReadGuard, ArcRwLockUpgradeableGuard, ArcRwLockWriteGuard, RwLock,
@@ -27,6 +33,8 @@ pub use self::{
         ArcRwMutexReadGuard, ArcRwMutexUpgradeableGuard, ArcRwMutexWriteGuard, RwMutex,
         RwMutexReadGuard, RwMutexUpgradeableGuard, RwMutexWriteGuard,
     },
+    rwsem::{RwSemReadGuard, RwSemWriteGuard, RwSemaphore},
+    seqlock::{SeqLock, SeqLockWriteGuard},
     spin::{ArcSpinLockGuard, SpinLock, SpinLockGuard},
     wait::{WaitQueue, Waiter, Waker},
 };
@@ -0,0 +1,140 @@
+// SPDX-License-Identifier: MPL-2.0
+
+use core::{
+    fmt,
+    sync::atomic::{AtomicIsize, Ordering},
+};
+
+use crate::cpu::local::PerCpu;
+
+/// A counter that is updated on each CPU without contention.
+///
+/// Each CPU accumulates its updates in a CPU-local delta. Once the delta reaches
+/// the batch size, it is folded into the global count. So the global count,
+/// which is returned by [`read_approx`], may deviate from the exact value by at
+/// most `batch * num_cpus()`. The exact value can be computed with [`read_exact`]
+/// by summing up all the CPU-local deltas, which is much slower.
+///
+/// [`read_approx`]: Self::read_approx
+/// [`read_exact`]: Self::read_exact
+pub struct PerCpuCounter {
+    count: AtomicIsize,
+    deltas: PerCpu<AtomicIsize>,
+    batch: isize,
+}
+
+impl PerCpuCounter {
+    /// The default batch size.
+    pub const DEFAULT_BATCH: isize = 32;
+
+    /// Creates a new counter with the initial value and the default batch size.
+    pub fn new(val: isize) -> Self {
+        Self::with_batch(val, Self::DEFAULT_BATCH)
+    }
+
+    /// Creates a new counter with the initial value and the batch size.
+    ///
+    /// # Panics
+    ///
+    /// This method panics if `batch` is not positive.
+    pub fn with_batch(val: isize, batch: isize) -> Self {
+        assert!(batch > 0);
+        Self {
+            count: AtomicIsize::new(val),
+            deltas: PerCpu::new(|_| AtomicIsize::new(0)),
+            batch,
+        }
+    }
+
+    /// Adds `delta` to the counter.
+    pub fn add(&self, delta: isize) {
+        let local = self.deltas.get();
+
+        // Only the current CPU updates its delta, except `set`, which resets
+        // all the deltas.
+        let new_delta = local.fetch_add(delta, Ordering::Relaxed) + delta;
+        if new_delta.abs() >= self.batch {
+            let folded = local.swap(0, Ordering::Relaxed);
+            self.count.fetch_add(folded, Ordering::Relaxed);
+        }
+    }
+
+    /// Subtracts `delta` from the counter.
+    pub fn sub(&self, delta: isize) {
+        self.add(-delta);
+    }
+
+    /// Returns the approximate value of the counter.
+    pub fn read_approx(&self) -> isize {
+        self.count.load(Ordering::Relaxed)
+    }
+
+    /// Returns the approximate value of the counter, which is clamped to be
+    /// non-negative.
+    ///
+    /// This is useful if the exact value never goes negative.
+    pub fn read_approx_positive(&self) -> usize {
+        self.read_approx().max(0) as usize
+    }
+
+    /// Returns the exact value of the counter.
+    ///
+    /// The value is exact if there are no concurrent updates.
+    pub fn read_exact(&self) -> isize {
+        let deltas: isize = self
+            .deltas
+            .iter()
+            .map(|(_, delta)| delta.load(Ordering::Relaxed))
+            .sum();
+        self.count.load(Ordering::Relaxed) + deltas
+    }
+
+    /// Sets the value of the counter.
+    ///
+    /// The concurrent updates may be lost.
+    pub fn set(&self, val: isize) {
+        for (_, delta) in self.deltas.iter() {
+            delta.store(0, Ordering::Relaxed);
+        }
+        self.count.store(val, Ordering::Relaxed);
+    }
+}
+
+impl fmt::Debug for PerCpuCounter {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("PerCpuCounter")
+            .field("approx", &self.read_approx())
+            .field("batch", &self.batch)
+            .finish()
+    }
+}
+
+#[cfg(ktest)]
+mod test {
+    use ostd_macros::ktest;
+
+    use super::*;
+    use crate::task::disable_preempt;
+
+    #[ktest]
+    fn batched_updates() {
+        // Stay on the same CPU so that the updates go to the same delta.
+        let _preempt_guard = disable_preempt();
+
+        let counter = PerCpuCounter::with_batch(10, 4);
+        counter.add(3);
+        assert_eq!(counter.read_approx(), 10);
+        assert_eq!(counter.read_exact(), 13);
+
+        counter.add(1);
+        assert_eq!(counter.read_approx(), 14);
+        assert_eq!(counter.read_exact(), 14);
+
+        counter.sub(20);
+        assert_eq!(counter.read_approx(), -6);
+        assert_eq!(counter.read_approx_positive(), 0);
+
+        counter.set(7);
+        assert_eq!(counter.read_exact(), 7);
+    }
+}
@@ -0,0 +1,262 @@
+// SPDX-License-Identifier: MPL-2.0
+
+use alloc::{sync::Arc, vec::Vec};
+use core::{
+    cell::UnsafeCell,
+    fmt,
+    ops::{Deref, DerefMut},
+};
+
+use super::{LocalIrqDisabled, SpinLock, Waiter, Waker};
+use crate::task::{atomic_mode::might_sleep, scheduler, Task};
+
+/// A sleeping mutex with priority inheritance (PI).
+///
+/// While a task is blocked on the mutex, the owner of the mutex inherits the
+/// priority of the task if it is higher, so that a high-priority task cannot
+/// be delayed indefinitely by medium-priority tasks that preempt a low-priority
+/// owner. The priorities are defined by the injected [`Scheduler`].
+///
+/// When the mutex is released, it is handed over to the waiter with the
+/// highest priority, or the earliest one if there is a tie.
+///
+/// [`Scheduler`]: crate::task::scheduler::Scheduler
+//
+// TODO: Propagate the inherited priority along a chain of owners, i.e., if the
+// owner is itself blocked on another PI mutex.
+pub struct PiMutex<T: ?Sized> {
+    state: SpinLock<PiMutexState, LocalIrqDisabled>,
+    val: UnsafeCell<T>,
+}
+
+struct PiMutexState {
+    owner: Option<Arc<Task>>,
+    /// The waiters in FIFO order.
+    waiters: Vec<PiWaiter>,
+}
+
+struct PiWaiter {
+    task: Arc<Task>,
+    priority: Option<u8>,
+    /// The waker, which is woken only if the mutex is handed over to the waiter.
+    waker: Arc<Waker>,
+}
+
+impl<T> PiMutex<T> {
+    /// Creates a new PI mutex.
+    #[cfg_attr(feature = "lockdep", track_caller)]
+    pub const fn new(val: T) -> Self {
+        Self {
+            state: SpinLock::new(PiMutexState {
+                owner: None,
+                waiters: Vec::new(),
+            }),
+            val: UnsafeCell::new(val),
+        }
+    }
+}
+
+impl<T: ?Sized> PiMutex<T> {
+    /// Acquires the mutex, sleeping until it can be acquired.
+    ///
+    /// # Panics
+    ///
+    /// This method panics if it is called in atomic mode or without a current task.
+    #[track_caller]
+    pub fn lock(&self) -> PiMutexGuard<T> {
+        might_sleep();
+
+        let current = Task::current().unwrap().cloned();
+        let mut state = self.state.lock();
+        if state.owner.is_none() {
+            state.owner = Some(current);
+            state.update_owner_priority(self.addr());
+            return PiMutexGuard { mutex: self };
+        }
+
+        let (waiter, waker) = Waiter::new_pair();
+        state.waiters.push(PiWaiter {
+            priority: scheduler::inheritable_priority(&current),
+            task: current,
+            waker,
+        });
+        state.update_owner_priority(self.addr());
+        drop(state);
+
+        // The mutex has been handed over to us once we are woken up.
+        waiter.wait();
+        PiMutexGuard { mutex: self }
+    }
+
+    /// Tries to acquire the mutex immediately.
+    pub fn try_lock(&self) -> Option<PiMutexGuard<T>> {
+        let mut state = self.state.lock();
+        if state.owner.is_some() {
+            return None;
+        }
+        state.owner = Some(Task::current()?.cloned());
+        state.update_owner_priority(self.addr());
+        Some(PiMutexGuard { mutex: self })
+    }
+
+    /// Returns a mutable reference to the underlying data.
+    ///
+    /// This method is zero-cost: By holding a mutable reference to the lock, the compiler has
+    /// already statically guaranteed that access to the data is exclusive.
+    pub fn get_mut(&mut self) -> &mut T {
+        self.val.get_mut()
+    }
+
+    /// Releases the mutex and hands it over to the waiter with the highest priority.
+    fn unlock(&self) {
+        let next_waker = {
+            let mut state = self.state.lock();
+            let old_owner = state.owner.take().unwrap();
+            old_owner
+                .inherited_priorities()
+                .update(&old_owner, self.addr(), None);
+
+            state.pick_next_waiter().map(|next| {
+                state.owner = Some(next.task);
+                state.update_owner_priority(self.addr());
+                next.waker
+            })
+        };
+
+        if let Some(waker) = next_waker {
+            waker.wake_up();
+        }
+    }
+
+    fn addr(&self) -> usize {
+        self as *const Self as *const () as usize
+    }
+}
+
+impl PiMutexState {
+    /// Removes and returns the waiter with the highest priority.
+    fn pick_next_waiter(&mut self) -> Option<PiWaiter> {
+        let (index, _) = self
+            .waiters
+            .iter()
+            .enumerate()
+            // `None` is less than `Some(_)`, but means the lowest priority.
+            .min_by_key(|(_, waiter)| waiter.priority.unwrap_or(u8::MAX))?;
+        Some(self.waiters.remove(index))
+    }
+
+    /// Lets the owner inherit the highest priority of the waiters.
+    fn update_owner_priority(&self, mutex: usize) {
+        let Some(owner) = self.owner.as_ref() else {
+            return;
+        };
+        let priority = self
+            .waiters
+            .iter()
+            .filter_map(|waiter| waiter.priority)
+            .min();
+        owner.inherited_priorities().update(owner, mutex, priority);
+    }
+}
+
+impl<T: ?Sized + fmt::Debug> fmt::Debug for PiMutex<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Debug::fmt(&self.val, f)
+    }
+}
+
+// SAFETY: Only the owner of the mutex can access the inner data.
+unsafe impl<T: ?Sized + Send> Send for PiMutex<T> {}
+unsafe impl<T: ?Sized + Send> Sync for PiMutex<T> {}
+
+/// A guard that provides exclusive access to the data protected by a [`PiMutex`].
+#[clippy::has_significant_drop]
+#[must_use]
+pub struct PiMutexGuard<'a, T: ?Sized> {
+    mutex: &'a PiMutex<T>,
+}
+
+impl<T: ?Sized> Deref for PiMutexGuard<'_, T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        // SAFETY: The mutex is owned by the current task.
+        unsafe { &*self.mutex.val.get() }
+    }
+}
+
+impl<T: ?Sized> DerefMut for PiMutexGuard<'_, T> {
+    fn deref_mut(&mut self) -> &mut T {
+        // SAFETY: The mutex is owned by the current task.
+        unsafe { &mut *self.mutex.val.get() }
+    }
+}
+
+impl<T: ?Sized> Drop for PiMutexGuard<'_, T> {
+    fn drop(&mut self) {
+        self.mutex.unlock();
+    }
+}
+
+impl<T: ?Sized + fmt::Debug> fmt::Debug for PiMutexGuard<'_, T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Debug::fmt(&**self, f)
+    }
+}
+
+impl<T: ?Sized> !Send for PiMutexGuard<'_, T> {}
+
+// SAFETY: The guard only provides shared access to the data through shared references.
+unsafe impl<T: ?Sized + Sync> Sync for PiMutexGuard<'_, T> {}
+
+/// The priorities that a task inherits from the waiters of the PI mutexes that
+/// it owns.
+#[derive(Debug)]
+pub(crate) struct InheritedPriorities {
+    /// The address of each PI mutex with waiters to inherit from, and the
+    /// highest priority of the waiters.
+    boosts: SpinLock<Vec<(usize, u8)>, LocalIrqDisabled>,
+}
+
+impl InheritedPriorities {
+    pub(crate) const fn new() -> Self {
+        Self {
+            boosts: SpinLock::new(Vec::new()),
+        }
+    }
+
+    /// Updates the priority inherited through the mutex, and lets the scheduler
+    /// apply the highest inherited priority to the task.
+    fn update(&self, task: &Task, mutex: usize, priority: Option<u8>) {
+        let mut boosts = self.boosts.lock();
+        let old_len = boosts.len();
+        boosts.retain(|(addr, _)| *addr != mutex);
+        if priority.is_none() && boosts.len() == old_len {
+            return;
+        }
+        if let Some(priority) = priority {
+            boosts.push((mutex, priority));
+        }
+
+        let highest = boosts.iter().map(|(_, priority)| *priority).min();
+        scheduler::set_inherited_priority(task, highest);
+    }
+}
+
+#[cfg(ktest)]
+mod test {
+    use ostd_macros::ktest;
+
+    use super::*;
+
+    #[ktest]
+    fn lock_and_try_lock() {
+        let mutex = PiMutex::new(1);
+        {
+            let mut guard = mutex.lock();
+            assert!(mutex.try_lock().is_none());
+            *guard += 1;
+        }
+        assert_eq!(*mutex.try_lock().unwrap(), 2);
+    }
+}
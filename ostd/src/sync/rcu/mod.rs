@@ -1,62 +1,124 @@
 // SPDX-License-Identifier: MPL-2.0
 
 //! Read-copy update (RCU).
-
-use core::marker::PhantomData;
-use core::ops::Deref;
-use core::sync::atomic::{
-    AtomicPtr,
-    Ordering::{AcqRel, Acquire},
+//!
+//! An [`Rcu`] pointer can be read without locks. A reader enters a read-side
+//! critical section with [`Rcu::read`], which disables preemption on the
+//! current CPU. An updater replaces the pointer atomically, and the old object
+//! is dropped after a _grace period_, i.e., after every CPU has passed a
+//! _quiescent state_ where it cannot be in a read-side critical section.
+//!
+//! The quiescent states are reported by the scheduler on context switches,
+//! when a CPU becomes idle, and on timer ticks that interrupt preemptible code.
+
+use alloc::{boxed::Box, sync::Arc};
+use core::{
+    marker::PhantomData,
+    ops::Deref,
+    sync::atomic::{AtomicBool, AtomicPtr, Ordering},
 };
 
+use spin::Once;
+
 use self::monitor::RcuMonitor;
-use crate::prelude::*;
-use crate::sync::WaitQueue;
+use crate::{
+    cpu::PinCurrentCpu,
+    cpu_local_cell,
+    sync::WaitQueue,
+    task::{disable_preempt, DisabledPreemptGuard},
+};
 
 mod monitor;
 mod owner_ptr;
 
 pub use owner_ptr::OwnerPtr;
 
+/// A pointer that is protected by read-copy update.
 pub struct Rcu<P: OwnerPtr> {
     ptr: AtomicPtr<<P as OwnerPtr>::Target>,
-    marker: PhantomData<P::Target>,
+    _marker: PhantomData<P>,
 }
 
 impl<P: OwnerPtr> Rcu<P> {
+    /// Creates a new RCU-protected pointer.
     pub fn new(ptr: P) -> Self {
-        let ptr = AtomicPtr::new(OwnerPtr::into_raw(ptr) as *mut _);
+        let ptr = AtomicPtr::new(OwnerPtr::into_raw(ptr).cast_mut());
         Self {
             ptr,
-            marker: PhantomData,
+            _marker: PhantomData,
         }
     }
 
-    pub fn get(&self) -> RcuReadGuard<'_, P> {
-        let obj = unsafe { &*self.ptr.load(Acquire) };
-        RcuReadGuard { obj, rcu: self }
+    /// Enters a read-side critical section and gets the current object.
+    ///
+    /// Preemption is disabled until the returned guard is dropped, so the
+    /// critical section must not sleep.
+    pub fn read(&self) -> RcuReadGuard<'_, P> {
+        let preempt_guard = disable_preempt();
+        // SAFETY: The pointer is valid until a grace period after it is replaced,
+        // which cannot end before the guard is dropped.
+        let obj = unsafe { &*self.ptr.load(Ordering::Acquire) };
+        RcuReadGuard {
+            obj,
+            _preempt_guard: preempt_guard,
+            _marker: PhantomData,
+        }
     }
 }
 
-impl<P: OwnerPtr + Send> Rcu<P> {
-    pub fn replace(&self, new_ptr: P) -> RcuReclaimer<P> {
-        let new_ptr = <P as OwnerPtr>::into_raw(new_ptr) as *mut _;
-        let old_ptr = {
-            let old_raw_ptr = self.ptr.swap(new_ptr, AcqRel);
-            unsafe { <P as OwnerPtr>::from_raw(old_raw_ptr) }
-        };
-        RcuReclaimer { ptr: old_ptr }
+impl<P: OwnerPtr + Send + 'static> Rcu<P> {
+    /// Replaces the current object with a new one.
+    ///
+    /// The old object is dropped after a grace period.
+    pub fn update(&self, new_ptr: P) {
+        let old_ptr = self.swap(new_ptr);
+        call_rcu(move || drop(old_ptr));
+    }
+
+    /// Replaces the current object with a new one and returns the old one.
+    ///
+    /// This method waits for a grace period, so that no reader can access the
+    /// old object once it returns. It must not be called in atomic mode.
+    pub fn replace(&self, new_ptr: P) -> P {
+        let old_ptr = self.swap(new_ptr);
+        synchronize_rcu();
+        old_ptr
+    }
+
+    fn swap(&self, new_ptr: P) -> P {
+        let new_ptr = <P as OwnerPtr>::into_raw(new_ptr).cast_mut();
+        let old_ptr = self.ptr.swap(new_ptr, Ordering::AcqRel);
+        // SAFETY: The pointer is returned by `into_raw` and is no longer stored.
+        unsafe { <P as OwnerPtr>::from_raw(old_ptr) }
     }
 }
 
+impl<P: OwnerPtr> Drop for Rcu<P> {
+    fn drop(&mut self) {
+        // SAFETY: No readers can exist since we have the mutable reference.
+        drop(unsafe { <P as OwnerPtr>::from_raw(*self.ptr.get_mut()) });
+    }
+}
+
+impl<P: OwnerPtr> core::fmt::Debug for Rcu<P>
+where
+    <P as OwnerPtr>::Target: core::fmt::Debug,
+{
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_tuple("Rcu").field(&*self.read()).finish()
+    }
+}
+
+/// A guard of a read-side critical section.
 #[clippy::has_significant_drop]
 #[must_use]
 pub struct RcuReadGuard<'a, P: OwnerPtr> {
     obj: &'a <P as OwnerPtr>::Target,
-    rcu: &'a Rcu<P>,
+    _preempt_guard: DisabledPreemptGuard,
+    _marker: PhantomData<&'a P>,
 }
 
-impl<'a, P: OwnerPtr> Deref for RcuReadGuard<'a, P> {
+impl<P: OwnerPtr> Deref for RcuReadGuard<'_, P> {
     type Target = <P as OwnerPtr>::Target;
 
     fn deref(&self) -> &Self::Target {
@@ -64,43 +126,128 @@ impl<'a, P: OwnerPtr> Deref for RcuReadGuard<'a, P> {
     }
 }
 
-#[repr(transparent)]
-pub struct RcuReclaimer<P> {
-    ptr: P,
+static RCU_MONITOR: Once<RcuMonitor> = Once::new();
+
+fn monitor() -> &'static RcuMonitor {
+    RCU_MONITOR.call_once(RcuMonitor::new)
 }
 
-impl<P: Send + 'static> RcuReclaimer<P> {
-    pub fn delay(mut self) {
-        let ptr: P = unsafe {
-            let ptr = core::mem::replace(&mut self.ptr, core::mem::uninitialized());
+/// Registers a callback to invoke after a grace period.
+///
+/// The callback is invoked on the CPU that completes the grace period with
+/// preemption disabled, so it must not sleep.
+pub fn call_rcu<F>(f: F)
+where
+    F: FnOnce() + Send + 'static,
+{
+    monitor().after_grace_period(Box::new(f));
+}
 
-            core::mem::forget(self);
+/// Waits for a grace period.
+///
+/// When this function returns, all the read-side critical sections that exist
+/// at the time of the call have ended. It must not be called in atomic mode.
+pub fn synchronize_rcu() {
+    crate::task::atomic_mode::might_sleep();
+
+    let wait_queue = Arc::new(WaitQueue::new());
+    let is_done = Arc::new(AtomicBool::new(false));
+    call_rcu({
+        let wait_queue = wait_queue.clone();
+        let is_done = is_done.clone();
+        move || {
+            is_done.store(true, Ordering::Release);
+            wait_queue.wake_all();
+        }
+    });
+    wait_queue.wait_until(|| is_done.load(Ordering::Acquire).then_some(()));
+}
 
-            ptr
-        };
-        get_singleton().after_grace_period(move || {
-            drop(ptr);
-        });
-    }
+/// Passes a quiescent state on the current CPU.
+///
+/// This function should be called only if the current CPU cannot be in any
+/// read-side critical sections, e.g., right before a context switch.
+pub(crate) fn pass_quiescent_state() {
+    let cpu = disable_preempt().current_cpu();
+    monitor().pass_quiescent_state(cpu);
 }
 
-impl<P> Drop for RcuReclaimer<P> {
-    fn drop(&mut self) {
-        let wq = Arc::new(WaitQueue::new());
-        get_singleton().after_grace_period({
-            let wq = wq.clone();
-            move || {
-                wq.wake_one();
-            }
-        });
-        wq.wait_until(|| Some(0u8));
+/// Passes a quiescent state on a timer tick if the interrupted code is preemptible.
+///
+/// The read-side critical sections disable preemption, so preemptible code, including the
+/// user code and the idle loop, cannot be in any of them. Like Linux's `rcu_sched_clock_irq`,
+/// this lets the grace periods complete even if a CPU does not switch tasks for a long time.
+///
+/// This function should be called in the top half of the timer interrupt.
+pub(crate) fn pass_quiescent_state_on_tick() {
+    if crate::task::is_preemptible() {
+        pass_quiescent_state();
     }
 }
 
-pub unsafe fn pass_quiescent_state() {
-    get_singleton().pass_quiescent_state()
+cpu_local_cell! {
+    /// Whether the current CPU is marked as idle.
+    static IS_IDLE: bool = false;
+}
+
+/// Marks the current CPU as idle, in which it is in an extended quiescent state.
+///
+/// Returns `false` if the CPU should not go idle since some callbacks are ready
+/// to be invoked. In this case, the CPU is still marked as idle.
+///
+/// This function must be called with local IRQs disabled.
+pub(crate) fn enter_idle() -> bool {
+    IS_IDLE.store(true);
+    let cpu = disable_preempt().current_cpu();
+    monitor().enter_idle(cpu)
+}
+
+/// Marks the current CPU as no longer idle.
+///
+/// This function must be called with local IRQs disabled. It is also called on
+/// interrupts, since interrupt handlers may enter read-side critical sections.
+pub(crate) fn exit_idle() {
+    if !IS_IDLE.load() {
+        return;
+    }
+    IS_IDLE.store(false);
+    let cpu = disable_preempt().current_cpu();
+    monitor().exit_idle(cpu);
 }
 
-fn get_singleton() -> &'static RcuMonitor {
-    todo!()
+#[cfg(ktest)]
+mod test {
+    use ostd_macros::ktest;
+
+    use super::*;
+
+    #[ktest]
+    fn read_after_update() {
+        let rcu = Rcu::new(Box::new(1));
+        assert_eq!(*rcu.read(), 1);
+        rcu.update(Box::new(2));
+        assert_eq!(*rcu.read(), 2);
+    }
+
+    #[ktest]
+    fn grace_period_while_spinning() {
+        // The current CPU does not switch tasks while spinning, so the grace
+        // period can only complete by the quiescent states on timer ticks.
+        let is_done = Arc::new(AtomicBool::new(false));
+        call_rcu({
+            let is_done = is_done.clone();
+            move || is_done.store(true, Ordering::Release)
+        });
+        while !is_done.load(Ordering::Acquire) {
+            core::hint::spin_loop();
+        }
+    }
+
+    #[ktest]
+    fn synchronize_while_others_spin() {
+        // The application processors spin without switching tasks until the
+        // kernel registers their entry, which never happens in the tests.
+        synchronize_rcu();
+        synchronize_rcu();
+    }
 }
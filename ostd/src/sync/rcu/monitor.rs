@@ -1,141 +1,139 @@
 // SPDX-License-Identifier: MPL-2.0
 
-use alloc::collections::VecDeque;
-use core::sync::atomic::{
-    AtomicBool,
-    Ordering::{Acquire, Relaxed, Release},
+use alloc::{boxed::Box, vec::Vec};
+use core::sync::atomic::{AtomicBool, Ordering};
+
+use crate::{
+    cpu::{all_cpus, CpuId, CpuSet},
+    sync::{LocalIrqDisabled, SpinLock},
+    task::disable_preempt,
 };
 
-#[cfg(target_arch = "x86_64")]
-use crate::arch::x86::cpu;
-use crate::prelude::*;
-use crate::sync::AtomicBits;
-use crate::sync::SpinLock;
+type Callbacks = Vec<Box<dyn FnOnce() + Send + 'static>>;
 
 /// A RCU monitor ensures the completion of _grace periods_ by keeping track
 /// of each CPU's passing _quiescent states_.
-pub struct RcuMonitor {
-    is_monitoring: AtomicBool,
-    state: SpinLock<State>,
+///
+/// An idle CPU is in an extended quiescent state, so it does not hold up the
+/// grace periods that start while it is idle.
+pub(super) struct RcuMonitor {
+    /// Whether there are callbacks that wait for or have passed grace periods.
+    ///
+    /// It is a hint for the fast path of passing quiescent states.
+    has_callbacks: AtomicBool,
+    state: SpinLock<State, LocalIrqDisabled>,
+}
+
+struct State {
+    /// The CPUs that have not passed a quiescent state in the current grace period.
+    pending_cpus: CpuSet,
+    /// The CPUs that are idle.
+    idle_cpus: CpuSet,
+    /// The callbacks to invoke after the current grace period.
+    ///
+    /// A grace period is in progress if and only if this is not empty.
+    current_callbacks: Callbacks,
+    /// The callbacks to invoke after the next grace period.
+    next_callbacks: Callbacks,
+    /// The callbacks whose grace periods have completed.
+    ready_callbacks: Callbacks,
 }
 
 impl RcuMonitor {
-    pub fn new(num_cpus: usize) -> Self {
+    pub(super) fn new() -> Self {
         Self {
-            is_monitoring: AtomicBool::new(false),
-            state: SpinLock::new(State::new(num_cpus)),
+            has_callbacks: AtomicBool::new(false),
+            state: SpinLock::new(State {
+                pending_cpus: CpuSet::new_empty(),
+                idle_cpus: CpuSet::new_empty(),
+                current_callbacks: Vec::new(),
+                next_callbacks: Vec::new(),
+                ready_callbacks: Vec::new(),
+            }),
         }
     }
 
-    pub unsafe fn pass_quiescent_state(&self) {
-        // Fast path
-        if !self.is_monitoring.load(Relaxed) {
+    /// Registers a callback to invoke after the next grace period.
+    pub(super) fn after_grace_period(&self, f: Box<dyn FnOnce() + Send + 'static>) {
+        let mut state = self.state.lock();
+        state.next_callbacks.push(f);
+        state.advance();
+        self.has_callbacks.store(true, Ordering::Relaxed);
+    }
+
+    /// Passes a quiescent state on the current CPU.
+    ///
+    /// The callbacks whose grace periods are completed are invoked with preemption
+    /// disabled.
+    pub(super) fn pass_quiescent_state(&self, cpu: CpuId) {
+        if !self.has_callbacks.load(Ordering::Relaxed) {
             return;
         }
 
-        // Check if the current GP is complete after passing the quiescent state
-        // on the current CPU. If GP is complete, take the callbacks of the current
-        // GP.
         let callbacks = {
-            let mut state = self.state.disable_irq().lock();
-            if state.current_gp.is_complete() {
-                return;
-            }
-
-            state.current_gp.pass_quiescent_state();
-            if !state.current_gp.is_complete() {
-                return;
-            }
-
-            // Now that the current GP is complete, take its callbacks
-            let current_callbacks = state.current_gp.take_callbacks();
-
-            // Check if we need to70G watch for a next GP
-            if !state.next_callbacks.is_empty() {
-                let callbacks = core::mem::take(&mut state.next_callbacks);
-                state.current_gp.restart(callbacks);
-            } else {
-                self.is_monitoring.store(false, Relaxed);
-            }
-
-            current_callbacks
+            let mut state = self.state.lock();
+            state.pending_cpus.remove(cpu);
+            state.advance();
+            let callbacks = core::mem::take(&mut state.ready_callbacks);
+            self.has_callbacks
+                .store(state.has_callbacks(), Ordering::Relaxed);
+            callbacks
         };
 
-        // Invoke the callbacks to notify the completion of GP
+        let _preempt_guard = disable_preempt();
         for f in callbacks {
-            (f)();
+            f();
         }
     }
 
-    pub fn after_grace_period<F>(&self, f: F)
-    where
-        F: FnOnce() -> () + Send + 'static,
-    {
-        let mut state = self.state.disable_irq().lock();
-
-        state.next_callbacks.push_back(Box::new(f));
-
-        if !state.current_gp.is_complete() {
-            return;
-        }
-
-        let callbacks = core::mem::take(&mut state.next_callbacks);
-        state.current_gp.restart(callbacks);
-        self.is_monitoring.store(true, Relaxed);
+    /// Marks the current CPU as idle.
+    ///
+    /// Returns `false` if some callbacks are ready to be invoked, in which case
+    /// the CPU should pass a quiescent state instead of going idle.
+    pub(super) fn enter_idle(&self, cpu: CpuId) -> bool {
+        let mut state = self.state.lock();
+        state.idle_cpus.add(cpu);
+        state.pending_cpus.remove(cpu);
+        state.advance();
+        state.ready_callbacks.is_empty()
     }
-}
 
-struct State {
-    current_gp: GracePeriod,
-    next_callbacks: Callbacks,
-}
-
-impl State {
-    pub fn new(num_cpus: usize) -> Self {
-        Self {
-            current_gp: GracePeriod::new(num_cpus),
-            next_callbacks: VecDeque::new(),
-        }
+    /// Marks the current CPU as no longer idle.
+    pub(super) fn exit_idle(&self, cpu: CpuId) {
+        self.state.lock().idle_cpus.remove(cpu);
     }
 }
 
-type Callbacks = VecDeque<Box<dyn FnOnce() -> () + Send + 'static>>;
-
-struct GracePeriod {
-    callbacks: Callbacks,
-    cpu_mask: AtomicBits,
-    is_complete: bool,
-}
-
-impl GracePeriod {
-    pub fn new(num_cpus: usize) -> Self {
-        Self {
-            callbacks: Default::default(),
-            cpu_mask: AtomicBits::new_zeroes(num_cpus),
-            is_complete: false,
-        }
-    }
-
-    pub fn is_complete(&self) -> bool {
-        self.is_complete
-    }
-
-    pub unsafe fn pass_quiescent_state(&mut self) {
-        let this_cpu = cpu::this_cpu();
-        self.cpu_mask.set(this_cpu as usize, true);
+impl State {
+    /// Completes the current grace period if all CPUs have passed quiescent
+    /// states, and starts the next grace period if needed.
+    fn advance(&mut self) {
+        loop {
+            let is_in_progress = !self.current_callbacks.is_empty();
+            if is_in_progress && !self.pending_cpus.is_empty() {
+                return;
+            }
+            if is_in_progress {
+                let callbacks = core::mem::take(&mut self.current_callbacks);
+                self.ready_callbacks.extend(callbacks);
+            }
+            if self.next_callbacks.is_empty() {
+                return;
+            }
 
-        if self.cpu_mask.is_full() {
-            self.is_complete = true;
+            self.current_callbacks = core::mem::take(&mut self.next_callbacks);
+            self.pending_cpus.clear();
+            for cpu in all_cpus() {
+                if !self.idle_cpus.contains(cpu) {
+                    self.pending_cpus.add(cpu);
+                }
+            }
         }
     }
 
-    pub fn take_callbacks(&mut self) -> Callbacks {
-        core::mem::take(&mut self.callbacks)
-    }
-
-    pub fn restart(&mut self, callbacks: Callbacks) {
-        self.is_complete = false;
-        self.cpu_mask.clear();
-        self.callbacks = callbacks;
+    fn has_callbacks(&self) -> bool {
+        !self.current_callbacks.is_empty()
+            || !self.next_callbacks.is_empty()
+            || !self.ready_callbacks.is_empty()
     }
 }
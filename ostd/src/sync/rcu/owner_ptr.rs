@@ -1,14 +1,11 @@
 // SPDX-License-Identifier: MPL-2.0
 
-use core::ptr::NonNull;
-
-use crate::prelude::*;
+use alloc::{boxed::Box, sync::Arc};
 
 /// A trait that abstracts pointers that have the ownership of the objects they
 /// refer to.
 ///
-/// The most typical examples smart pointer types like `Box<T>` and `Arc<T>`.
-///
+/// The most typical examples are smart pointer types like `Box<T>` and `Arc<T>`,
 /// which can be converted to and from the raw pointer type of `*const T`.
 pub trait OwnerPtr {
     /// The target type that this pointer refers to.
@@ -38,7 +35,8 @@ impl<T> OwnerPtr for Box<T> {
     }
 
     unsafe fn from_raw(ptr: *const Self::Target) -> Self {
-        Box::from_raw(ptr as *mut _)
+        // SAFETY: The pointer is returned by `Box::into_raw`.
+        unsafe { Box::from_raw(ptr as *mut _) }
     }
 }
 
@@ -50,31 +48,7 @@ impl<T> OwnerPtr for Arc<T> {
     }
 
     unsafe fn from_raw(ptr: *const Self::Target) -> Self {
-        Arc::from_raw(ptr)
-    }
-}
-
-impl<P> OwnerPtr for Option<P>
-where
-    P: OwnerPtr,
-    // We cannot support fat pointers, e.g., when `Target: dyn Trait`.
-    // This is because Rust does not allow fat null pointers. Yet,
-    // we need the null pointer to represent `None`.
-    // See https://github.com/rust-lang/rust/issues/66316.
-    <P as OwnerPtr>::Target: Sized,
-{
-    type Target = P::Target;
-
-    fn into_raw(self) -> *const Self::Target {
-        self.map(|p| <P as OwnerPtr>::into_raw(p))
-            .unwrap_or(core::ptr::null())
-    }
-
-    unsafe fn from_raw(ptr: *const Self::Target) -> Self {
-        if ptr.is_null() {
-            Some(<P as OwnerPtr>::from_raw(ptr))
-        } else {
-            None
-        }
+        // SAFETY: The pointer is returned by `Arc::into_raw`.
+        unsafe { Arc::from_raw(ptr) }
     }
 }
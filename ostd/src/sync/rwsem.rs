@@ -0,0 +1,304 @@
+// SPDX-License-Identifier: MPL-2.0
+
+use alloc::{collections::VecDeque, sync::Arc, vec::Vec};
+use core::{
+    cell::UnsafeCell,
+    fmt,
+    ops::{Deref, DerefMut},
+};
+
+use super::{LocalIrqDisabled, SpinLock, Waiter, Waker};
+use crate::task::atomic_mode::might_sleep;
+
+/// A reader-writer semaphore that is fair to writers.
+///
+/// Like [`RwMutex`], the semaphore allows for multiple readers or one writer,
+/// and the tasks that fail to acquire it sleep. Unlike [`RwMutex`], a new reader
+/// cannot acquire the semaphore if any tasks are waiting for it, so a stream of
+/// readers cannot starve a writer. The waiting tasks acquire the semaphore in
+/// FIFO order: when it is released, it is handed over to the first waiting
+/// writer, or to all the consecutive waiting readers at the front of the queue.
+///
+/// [`RwMutex`]: super::RwMutex
+pub struct RwSemaphore<T: ?Sized> {
+    state: SpinLock<RwSemState, LocalIrqDisabled>,
+    val: UnsafeCell<T>,
+}
+
+struct RwSemState {
+    nr_readers: usize,
+    has_writer: bool,
+    /// The waiters in FIFO order.
+    waiters: VecDeque<RwSemWaiter>,
+}
+
+struct RwSemWaiter {
+    is_writer: bool,
+    /// The waker, which is woken only if the semaphore is handed over to the waiter.
+    waker: Arc<Waker>,
+}
+
+impl<T> RwSemaphore<T> {
+    /// Creates a new reader-writer semaphore.
+    #[cfg_attr(feature = "lockdep", track_caller)]
+    pub const fn new(val: T) -> Self {
+        Self {
+            state: SpinLock::new(RwSemState {
+                nr_readers: 0,
+                has_writer: false,
+                waiters: VecDeque::new(),
+            }),
+            val: UnsafeCell::new(val),
+        }
+    }
+}
+
+impl<T: ?Sized> RwSemaphore<T> {
+    /// Acquires the semaphore for reading, sleeping until it can be acquired.
+    #[track_caller]
+    pub fn read(&self) -> RwSemReadGuard<T> {
+        might_sleep();
+        self.acquire(false);
+        RwSemReadGuard { sem: self }
+    }
+
+    /// Acquires the semaphore for writing, sleeping until it can be acquired.
+    #[track_caller]
+    pub fn write(&self) -> RwSemWriteGuard<T> {
+        might_sleep();
+        self.acquire(true);
+        RwSemWriteGuard { sem: self }
+    }
+
+    /// Tries to acquire the semaphore for reading immediately.
+    ///
+    /// This fails if there is a writer holding or waiting for the semaphore.
+    pub fn try_read(&self) -> Option<RwSemReadGuard<T>> {
+        // Cannot be reduced to `then_some`, or the dropping of the temporary guard
+        // will release the semaphore.
+        self.state
+            .lock()
+            .try_acquire(false)
+            .then(|| RwSemReadGuard { sem: self })
+    }
+
+    /// Tries to acquire the semaphore for writing immediately.
+    pub fn try_write(&self) -> Option<RwSemWriteGuard<T>> {
+        self.state
+            .lock()
+            .try_acquire(true)
+            .then(|| RwSemWriteGuard { sem: self })
+    }
+
+    /// Returns a mutable reference to the underlying data.
+    ///
+    /// This method is zero-cost: By holding a mutable reference to the lock, the compiler has
+    /// already statically guaranteed that access to the data is exclusive.
+    pub fn get_mut(&mut self) -> &mut T {
+        self.val.get_mut()
+    }
+
+    #[track_caller]
+    fn acquire(&self, is_writer: bool) {
+        let mut state = self.state.lock();
+        if state.try_acquire(is_writer) {
+            return;
+        }
+
+        let (waiter, waker) = Waiter::new_pair();
+        state.waiters.push_back(RwSemWaiter { is_writer, waker });
+        drop(state);
+
+        // The semaphore has been handed over to us once we are woken up.
+        waiter.wait();
+    }
+
+    /// Releases the semaphore and hands it over to the waiters.
+    fn release(&self, is_writer: bool) {
+        let wakers = {
+            let mut state = self.state.lock();
+            if is_writer {
+                state.has_writer = false;
+            } else {
+                state.nr_readers -= 1;
+            }
+            state.hand_over()
+        };
+
+        for waker in wakers {
+            waker.wake_up();
+        }
+    }
+}
+
+impl RwSemState {
+    fn try_acquire(&mut self, is_writer: bool) -> bool {
+        if self.has_writer || !self.waiters.is_empty() {
+            return false;
+        }
+
+        if is_writer {
+            if self.nr_readers > 0 {
+                return false;
+            }
+            self.has_writer = true;
+        } else {
+            self.nr_readers += 1;
+        }
+        true
+    }
+
+    /// Hands over the semaphore to the waiters at the front of the queue if
+    /// possible, returning their wakers.
+    fn hand_over(&mut self) -> Vec<Arc<Waker>> {
+        let mut wakers = Vec::new();
+
+        while let Some(waiter) = self.waiters.front() {
+            if self.has_writer || (waiter.is_writer && self.nr_readers > 0) {
+                break;
+            }
+
+            let waiter = self.waiters.pop_front().unwrap();
+            if waiter.is_writer {
+                self.has_writer = true;
+            } else {
+                self.nr_readers += 1;
+            }
+            wakers.push(waiter.waker);
+        }
+
+        wakers
+    }
+}
+
+impl<T: ?Sized + fmt::Debug> fmt::Debug for RwSemaphore<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Debug::fmt(&self.val, f)
+    }
+}
+
+// SAFETY: The readers only get shared references to the inner data, and the
+// writer is exclusive.
+unsafe impl<T: ?Sized + Send> Send for RwSemaphore<T> {}
+unsafe impl<T: ?Sized + Send + Sync> Sync for RwSemaphore<T> {}
+
+/// A guard that provides shared read access to the data protected by a [`RwSemaphore`].
+#[clippy::has_significant_drop]
+#[must_use]
+pub struct RwSemReadGuard<'a, T: ?Sized> {
+    sem: &'a RwSemaphore<T>,
+}
+
+impl<T: ?Sized> Deref for RwSemReadGuard<'_, T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        // SAFETY: The semaphore is held for reading, so there is no writer.
+        unsafe { &*self.sem.val.get() }
+    }
+}
+
+impl<T: ?Sized> Drop for RwSemReadGuard<'_, T> {
+    fn drop(&mut self) {
+        self.sem.release(false);
+    }
+}
+
+impl<T: ?Sized> !Send for RwSemReadGuard<'_, T> {}
+
+// SAFETY: The guard only provides shared access to the data.
+unsafe impl<T: ?Sized + Sync> Sync for RwSemReadGuard<'_, T> {}
+
+/// A guard that provides exclusive write access to the data protected by a [`RwSemaphore`].
+#[clippy::has_significant_drop]
+#[must_use]
+pub struct RwSemWriteGuard<'a, T: ?Sized> {
+    sem: &'a RwSemaphore<T>,
+}
+
+impl<'a, T: ?Sized> RwSemWriteGuard<'a, T> {
+    /// Atomically downgrades the write guard to a read guard.
+    ///
+    /// The waiting readers at the front of the queue acquire the semaphore as well.
+    pub fn downgrade(self) -> RwSemReadGuard<'a, T> {
+        let sem = self.sem;
+        core::mem::forget(self);
+
+        let wakers = {
+            let mut state = sem.state.lock();
+            state.has_writer = false;
+            state.nr_readers += 1;
+            state.hand_over()
+        };
+        for waker in wakers {
+            waker.wake_up();
+        }
+
+        RwSemReadGuard { sem }
+    }
+}
+
+impl<T: ?Sized> Deref for RwSemWriteGuard<'_, T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        // SAFETY: The semaphore is held for writing, so the access is exclusive.
+        unsafe { &*self.sem.val.get() }
+    }
+}
+
+impl<T: ?Sized> DerefMut for RwSemWriteGuard<'_, T> {
+    fn deref_mut(&mut self) -> &mut T {
+        // SAFETY: The semaphore is held for writing, so the access is exclusive.
+        unsafe { &mut *self.sem.val.get() }
+    }
+}
+
+impl<T: ?Sized> Drop for RwSemWriteGuard<'_, T> {
+    fn drop(&mut self) {
+        self.sem.release(true);
+    }
+}
+
+impl<T: ?Sized> !Send for RwSemWriteGuard<'_, T> {}
+
+// SAFETY: The guard only provides shared access to the data through shared references.
+unsafe impl<T: ?Sized + Sync> Sync for RwSemWriteGuard<'_, T> {}
+
+impl<T: ?Sized + fmt::Debug> fmt::Debug for RwSemReadGuard<'_, T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Debug::fmt(&**self, f)
+    }
+}
+
+impl<T: ?Sized + fmt::Debug> fmt::Debug for RwSemWriteGuard<'_, T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Debug::fmt(&**self, f)
+    }
+}
+
+#[cfg(ktest)]
+mod test {
+    use ostd_macros::ktest;
+
+    use super::*;
+
+    #[ktest]
+    fn readers_and_writer() {
+        let sem = RwSemaphore::new(1);
+        {
+            let r1 = sem.read();
+            let r2 = sem.try_read().unwrap();
+            assert_eq!(*r1 + *r2, 2);
+            assert!(sem.try_write().is_none());
+        }
+
+        let mut writer = sem.write();
+        *writer = 2;
+        assert!(sem.try_read().is_none());
+
+        let reader = writer.downgrade();
+        assert_eq!(*reader, 2);
+        assert!(sem.try_read().is_some());
+    }
+}
@@ -0,0 +1,146 @@
+// SPDX-License-Identifier: MPL-2.0
+
+use core::{
+    cell::UnsafeCell,
+    fmt,
+    ops::{Deref, DerefMut},
+    sync::atomic::{fence, AtomicUsize, Ordering},
+};
+
+use super::{LocalIrqDisabled, SpinLock, SpinLockGuard};
+
+/// A sequence lock.
+///
+/// A sequence lock protects a small `Copy` value that is read much more often
+/// than written, such as a timestamp. Readers never block writers: a reader
+/// copies the value without acquiring any lock and retries if a writer has
+/// modified the value in the meantime. Writers are serialized by a spin lock,
+/// with local IRQs disabled so that a reader in an interrupt handler cannot spin
+/// on a write that it has interrupted.
+pub struct SeqLock<T: Copy> {
+    /// The sequence number, which is odd if and only if a write is in progress.
+    seq: AtomicUsize,
+    writer: SpinLock<(), LocalIrqDisabled>,
+    val: UnsafeCell<T>,
+}
+
+// SAFETY: The value is only copied out by the readers, and writers are serialized.
+unsafe impl<T: Copy + Send> Send for SeqLock<T> {}
+// SAFETY: The value is only copied out by the readers, and writers are serialized.
+unsafe impl<T: Copy + Send> Sync for SeqLock<T> {}
+
+impl<T: Copy> SeqLock<T> {
+    /// Creates a new sequence lock.
+    #[cfg_attr(feature = "lockdep", track_caller)]
+    pub const fn new(val: T) -> Self {
+        Self {
+            seq: AtomicUsize::new(0),
+            writer: SpinLock::new(()),
+            val: UnsafeCell::new(val),
+        }
+    }
+
+    /// Reads the value.
+    ///
+    /// This method spins while a write is in progress.
+    pub fn read(&self) -> T {
+        loop {
+            if let Some(val) = self.try_read() {
+                return val;
+            }
+            core::hint::spin_loop();
+        }
+    }
+
+    /// Tries to read the value, returning `None` if it is being written.
+    pub fn try_read(&self) -> Option<T> {
+        let seq = self.seq.load(Ordering::Acquire);
+        if seq % 2 == 1 {
+            return None;
+        }
+
+        // SAFETY: The pointer is valid. The copy may be torn by a concurrent write,
+        // in which case the sequence number changes and the copy is discarded.
+        let val = unsafe { self.val.get().read_volatile() };
+
+        fence(Ordering::Acquire);
+        (self.seq.load(Ordering::Relaxed) == seq).then_some(val)
+    }
+
+    /// Acquires the write lock for mutable access.
+    pub fn write(&self) -> SeqLockWriteGuard<'_, T> {
+        let writer_guard = self.writer.lock();
+        self.seq.fetch_add(1, Ordering::Relaxed);
+        fence(Ordering::Release);
+        SeqLockWriteGuard {
+            lock: self,
+            _writer_guard: writer_guard,
+        }
+    }
+
+    /// Replaces the value.
+    pub fn set(&self, val: T) {
+        *self.write() = val;
+    }
+}
+
+impl<T: Copy + fmt::Debug> fmt::Debug for SeqLock<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("SeqLock").field(&self.read()).finish()
+    }
+}
+
+/// A guard that provides exclusive write access to the value of a [`SeqLock`].
+#[clippy::has_significant_drop]
+#[must_use]
+pub struct SeqLockWriteGuard<'a, T: Copy> {
+    lock: &'a SeqLock<T>,
+    _writer_guard: SpinLockGuard<'a, (), LocalIrqDisabled>,
+}
+
+impl<T: Copy> Deref for SeqLockWriteGuard<'_, T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        // SAFETY: The writer lock is held, so no one else can modify the value.
+        unsafe { &*self.lock.val.get() }
+    }
+}
+
+impl<T: Copy> DerefMut for SeqLockWriteGuard<'_, T> {
+    fn deref_mut(&mut self) -> &mut T {
+        // SAFETY: The writer lock is held, and the readers discard what they read
+        // while the sequence number is odd.
+        unsafe { &mut *self.lock.val.get() }
+    }
+}
+
+impl<T: Copy> Drop for SeqLockWriteGuard<'_, T> {
+    fn drop(&mut self) {
+        self.lock.seq.fetch_add(1, Ordering::Release);
+    }
+}
+
+#[cfg(ktest)]
+mod test {
+    use ostd_macros::ktest;
+
+    use super::*;
+
+    #[ktest]
+    fn read_and_write() {
+        let lock = SeqLock::new((1u64, 2u64));
+        assert_eq!(lock.read(), (1, 2));
+
+        {
+            let mut guard = lock.write();
+            guard.0 = 3;
+            assert_eq!(lock.try_read(), None);
+            guard.1 = 4;
+        }
+        assert_eq!(lock.read(), (3, 4));
+
+        lock.set((5, 6));
+        assert_eq!(lock.try_read(), Some((5, 6)));
+    }
+}
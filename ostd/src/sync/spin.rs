@@ -3,6 +3,8 @@
 #![allow(dead_code)]
 
 use alloc::sync::Arc;
+#[cfg(feature = "lockdep")]
+use core::panic::Location;
 use core::{
     cell::UnsafeCell,
     fmt,
@@ -38,14 +40,20 @@ pub struct SpinLock<T: ?Sized, G = PreemptDisabled> {
 
 struct SpinLockInner<T: ?Sized> {
     lock: AtomicBool,
+    /// Where the lock is created, which identifies its lock class.
+    #[cfg(feature = "lockdep")]
+    class: &'static Location<'static>,
     val: UnsafeCell<T>,
 }
 
 impl<T, G> SpinLock<T, G> {
     /// Creates a new spin lock.
+    #[cfg_attr(feature = "lockdep", track_caller)]
     pub const fn new(val: T) -> Self {
         let lock_inner = SpinLockInner {
             lock: AtomicBool::new(false),
+            #[cfg(feature = "lockdep")]
+            class: Location::caller(),
             val: UnsafeCell::new(val),
         };
         Self {
@@ -71,9 +79,12 @@ impl<T: ?Sized> SpinLock<T, PreemptDisabled> {
 
 impl<T: ?Sized, G: Guardian> SpinLock<T, G> {
     /// Acquires the spin lock.
+    #[cfg_attr(feature = "lockdep", track_caller)]
     pub fn lock(&self) -> SpinLockGuard<T, G> {
         // Notice the guard must be created before acquiring the lock.
         let inner_guard = G::guard();
+        #[cfg(feature = "lockdep")]
+        self.lockdep_acquire(false);
         self.acquire_lock();
         SpinLockGuard_ {
             lock: self,
@@ -87,8 +98,11 @@ impl<T: ?Sized, G: Guardian> SpinLock<T, G> {
     /// for compile-time checked lifetimes of the lock guard.
     ///
     /// [`lock`]: Self::lock
+    #[cfg_attr(feature = "lockdep", track_caller)]
     pub fn lock_arc(self: &Arc<Self>) -> ArcSpinLockGuard<T, G> {
         let inner_guard = G::guard();
+        #[cfg(feature = "lockdep")]
+        self.lockdep_acquire(false);
         self.acquire_lock();
         SpinLockGuard_ {
             lock: self.clone(),
@@ -97,9 +111,12 @@ impl<T: ?Sized, G: Guardian> SpinLock<T, G> {
     }
 
     /// Tries acquiring the spin lock immedidately.
+    #[cfg_attr(feature = "lockdep", track_caller)]
     pub fn try_lock(&self) -> Option<SpinLockGuard<T, G>> {
         let inner_guard = G::guard();
         if self.try_acquire_lock() {
+            #[cfg(feature = "lockdep")]
+            self.lockdep_acquire(true);
             let lock_guard = SpinLockGuard_ {
                 lock: self,
                 guard: inner_guard,
@@ -132,8 +149,26 @@ impl<T: ?Sized, G: Guardian> SpinLock<T, G> {
     }
 
     fn release_lock(&self) {
+        #[cfg(feature = "lockdep")]
+        super::lockdep::release(self.addr());
         self.inner.lock.store(false, Ordering::Release);
     }
+
+    #[cfg(feature = "lockdep")]
+    #[track_caller]
+    fn lockdep_acquire(&self, is_trylock: bool) {
+        super::lockdep::acquire(
+            self.addr(),
+            self.inner.class,
+            Location::caller(),
+            is_trylock,
+        );
+    }
+
+    #[cfg(feature = "lockdep")]
+    fn addr(&self) -> usize {
+        self as *const Self as *const () as usize
+    }
 }
 
 impl<T: ?Sized + fmt::Debug, G> fmt::Debug for SpinLock<T, G> {
@@ -48,6 +48,7 @@ pub struct WaitQueue {
 
 impl WaitQueue {
     /// Creates a new, empty wait queue.
+    #[cfg_attr(feature = "lockdep", track_caller)]
     pub const fn new() -> Self {
         WaitQueue {
             num_wakers: AtomicU32::new(0),
@@ -270,9 +271,11 @@ impl Waker {
 
     #[track_caller]
     fn do_wait(&self) {
+        self.task.set_blocked(true);
         while !self.has_woken.swap(false, Ordering::Acquire) {
             scheduler::park_current(|| self.has_woken.load(Ordering::Acquire));
         }
+        self.task.set_blocked(false);
     }
 
     fn close(&self) {
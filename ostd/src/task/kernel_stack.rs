@@ -1,5 +1,8 @@
 // SPDX-License-Identifier: MPL-2.0
 
+use core::ops::Range;
+
+use super::Task;
 use crate::{
     impl_frame_meta_for,
     mm::{
@@ -27,7 +30,6 @@ pub const DEFAULT_STACK_SIZE_IN_PAGES: u32 = 128;
 pub static KERNEL_STACK_SIZE: usize = STACK_SIZE_IN_PAGES as usize * PAGE_SIZE;
 
 #[derive(Debug)]
-#[allow(dead_code)]
 pub struct KernelStack {
     kvirt_area: KVirtArea<Tracked>,
     end_vaddr: Vaddr,
@@ -55,6 +57,8 @@ impl KernelStack {
             priv_flags: PrivilegedPageFlags::empty(),
         };
         new_kvirt_area.map_pages(mapped_start..mapped_end, pages, prop);
+        #[cfg(feature = "kasan")]
+        crate::mm::kasan::on_kernel_stack_alloc(new_kvirt_area.range(), mapped_start..mapped_end);
 
         Ok(Self {
             kvirt_area: new_kvirt_area,
@@ -66,6 +70,33 @@ impl KernelStack {
     pub fn end_vaddr(&self) -> Vaddr {
         self.end_vaddr
     }
+
+    /// Returns the range of the mapped stack pages.
+    pub fn range(&self) -> Range<Vaddr> {
+        self.end_vaddr - KERNEL_STACK_SIZE..self.end_vaddr
+    }
+
+    /// Returns whether the address is in the guard pages around the stack.
+    pub fn is_in_guard_page(&self, vaddr: Vaddr) -> bool {
+        self.has_guard_page
+            && self.kvirt_area.range().contains(&vaddr)
+            && !self.range().contains(&vaddr)
+    }
+}
+
+/// Checks whether an access to `vaddr` is an overflow of the current task's kernel stack.
+///
+/// If it is, the range of the kernel stack is returned. This is used to diagnose kernel
+/// page faults and double faults, which are otherwise hard to tell from memory corruptions.
+pub(crate) fn find_overflowed_stack(vaddr: Vaddr) -> Option<Range<Vaddr>> {
+    let current_task = Task::current()?;
+    let kstack = &current_task.kstack;
+    kstack.is_in_guard_page(vaddr).then(|| kstack.range())
+}
+
+/// Returns the range of the kernel stack of the current task.
+pub(crate) fn current_kernel_stack() -> Option<Range<Vaddr>> {
+    Some(Task::current()?.kstack.range())
 }
 
 const fn parse_u32_or_default(size: Option<&str>, default: u32) -> u32 {
@@ -8,6 +8,7 @@ mod preempt;
 mod processor;
 pub mod scheduler;
 mod utils;
+pub mod watchdog;
 
 use core::{
     any::Any,
@@ -15,10 +16,13 @@ use core::{
     cell::{Cell, SyncUnsafeCell},
     ops::Deref,
     ptr::NonNull,
+    sync::atomic::{AtomicU64, Ordering},
+    time::Duration,
 };
 
 use kernel_stack::KernelStack;
-pub(crate) use preempt::cpu_local::reset_preempt_info;
+pub(crate) use kernel_stack::{current_kernel_stack, find_overflowed_stack};
+pub(crate) use preempt::cpu_local::{is_preemptible, reset_preempt_info};
 use processor::current_task;
 use utils::ForceSync;
 
@@ -27,7 +31,7 @@ pub use self::{
     scheduler::info::{AtomicCpuId, TaskScheduleInfo},
 };
 pub(crate) use crate::arch::task::{context_switch, TaskContext};
-use crate::{prelude::*, trap::in_interrupt_context, user::UserSpace};
+use crate::{prelude::*, sync::InheritedPriorities, trap::in_interrupt_context, user::UserSpace};
 
 /// A task that executes a function to the end.
 ///
@@ -45,10 +49,13 @@ pub struct Task {
     user_space: Option<Arc<UserSpace>>,
     ctx: SyncUnsafeCell<TaskContext>,
     /// kernel stack, note that the top is SyscallFrame/TrapFrame
-    #[allow(dead_code)]
     kstack: KernelStack,
 
     schedule_info: TaskScheduleInfo,
+    inherited_priorities: InheritedPriorities,
+    /// The monotonic time in nanoseconds when the task starts to wait, or zero if it is
+    /// not waiting.
+    blocked_since_ns: AtomicU64,
 }
 
 impl Task {
@@ -66,22 +73,6 @@ impl Task {
         &self.ctx
     }
 
-    /// Sets thread-local storage pointer.
-    pub fn set_tls_pointer(&self, tls: usize) {
-        let ctx_ptr = self.ctx.get();
-
-        // SAFETY: it's safe to set user tls pointer in kernel context.
-        unsafe { (*ctx_ptr).set_tls_pointer(tls) }
-    }
-
-    /// Gets thread-local storage pointer.
-    pub fn tls_pointer(&self) -> usize {
-        let ctx_ptr = self.ctx.get();
-
-        // SAFETY: it's safe to get user tls pointer in kernel context.
-        unsafe { (*ctx_ptr).tls_pointer() }
-    }
-
     /// Yields execution so that another task may be scheduled.
     ///
     /// Note that this method cannot be simply named "yield" as the name is
@@ -109,6 +100,58 @@ impl Task {
         &self.schedule_info
     }
 
+    /// Returns the priorities inherited from the waiters of the PI mutexes that
+    /// the task owns.
+    pub(crate) fn inherited_priorities(&self) -> &InheritedPriorities {
+        &self.inherited_priorities
+    }
+
+    /// Marks whether the task is waiting to be woken up.
+    pub(crate) fn set_blocked(&self, is_blocked: bool) {
+        let since_ns = if is_blocked {
+            // Zero means that the task is not waiting.
+            crate::timer::monotonic_ns().max(1)
+        } else {
+            0
+        };
+        self.blocked_since_ns.store(since_ns, Ordering::Relaxed);
+    }
+
+    /// Returns how long the task has been waiting to be woken up.
+    ///
+    /// It returns `None` if the task is not waiting.
+    pub fn blocked_duration(&self) -> Option<Duration> {
+        let since_ns = self.blocked_since_ns.load(Ordering::Relaxed);
+        if since_ns == 0 {
+            return None;
+        }
+        let now_ns = crate::timer::monotonic_ns();
+        Some(Duration::from_nanos(now_ns.saturating_sub(since_ns)))
+    }
+
+    /// Walks the call chain of the task where it is waiting to be woken up.
+    ///
+    /// The visitor is called with the address where the task resumes after it is
+    /// switched back in first, and then with the return address of each caller, until
+    /// it returns `false` or the chain ends. Nothing is visited if the task is not
+    /// waiting.
+    ///
+    /// The call chain is best effort. The task may be woken up and start running
+    /// during the walk, in which case the visited addresses can be stale.
+    pub fn walk_blocked_stack(&self, mut visit: impl FnMut(Vaddr) -> bool) {
+        if self.blocked_since_ns.load(Ordering::Relaxed) == 0 {
+            return;
+        }
+
+        // SAFETY: The context is only written when the task is switched out. Reading it
+        // concurrently may produce stale values, which are only used as hints.
+        let ctx = unsafe { self.ctx.get().read_volatile() };
+        if !visit(ctx.instruction_pointer()) {
+            return;
+        }
+        crate::arch::trap::walk_frame_pointers(ctx.frame_pointer(), self.kstack.range(), visit);
+    }
+
     /// Returns the user space of this task, if it has.
     pub fn user_space(&self) -> Option<&Arc<UserSpace>> {
         if self.user_space.is_some() {
@@ -225,9 +268,6 @@ impl TaskOptions {
         let kstack = KernelStack::new_with_guard_page()?;
 
         let mut ctx = SyncUnsafeCell::new(TaskContext::default());
-        if let Some(user_space) = self.user_space.as_ref() {
-            ctx.get_mut().set_tls_pointer(user_space.tls_pointer());
-        };
         ctx.get_mut()
             .set_instruction_pointer(kernel_task_entry as usize);
         // We should reserve space for the return address in the stack, otherwise
@@ -250,6 +290,8 @@ impl TaskOptions {
             schedule_info: TaskScheduleInfo {
                 cpu: AtomicCpuId::default(),
             },
+            inherited_priorities: InheritedPriorities::new(),
+            blocked_since_ns: AtomicU64::new(0),
         };
 
         Ok(new_task)
@@ -355,7 +397,9 @@ pub trait TaskContextApi {
 
 #[cfg(ktest)]
 mod test {
-    use crate::prelude::*;
+    use core::sync::atomic::{AtomicBool, Ordering};
+
+    use crate::{prelude::*, sync::WaitQueue, task::Task};
 
     #[ktest]
     fn create_task() {
@@ -380,4 +424,36 @@ mod test {
         };
         let _ = crate::task::TaskOptions::new(task).data(()).spawn();
     }
+
+    #[ktest]
+    fn blocked_duration() {
+        let queue = Arc::new(WaitQueue::new());
+        let is_done = Arc::new(AtomicBool::new(false));
+        let task = {
+            let queue = queue.clone();
+            let is_done = is_done.clone();
+            crate::task::TaskOptions::new(move || {
+                queue.wait_until(|| is_done.load(Ordering::Relaxed).then_some(()));
+            })
+            .data(())
+            .spawn()
+            .unwrap()
+        };
+
+        while task.blocked_duration().is_none() {
+            Task::yield_now();
+        }
+        let mut depth = 0;
+        task.walk_blocked_stack(|_| {
+            depth += 1;
+            true
+        });
+        assert!(depth > 0);
+
+        is_done.store(true, Ordering::Relaxed);
+        queue.wake_all();
+        while task.blocked_duration().is_some() {
+            Task::yield_now();
+        }
+    }
 }
@@ -27,7 +27,6 @@ pub(in crate::task) fn should_preempt() -> bool {
     PREEMPT_INFO.load() == 0
 }
 
-#[allow(dead_code)]
 pub(in crate::task) fn need_preempt() -> bool {
     PREEMPT_INFO.load() & NEED_PREEMPT_MASK == 0
 }
@@ -53,6 +52,14 @@ pub(in crate::task) fn dec_guard_count() {
     PREEMPT_INFO.sub_assign(1);
 }
 
+/// Returns whether the current CPU holds no `DisabledPreemptGuard`.
+///
+/// In the interrupt context, it tells whether the interrupted code is preemptible, since the
+/// interrupt handlers do not disable preemption until the bottom half is processed.
+pub(crate) fn is_preemptible() -> bool {
+    get_guard_count() == 0
+}
+
 cpu_local_cell! {
     static PREEMPT_INFO: u32 = NEED_PREEMPT_MASK;
 }
@@ -1,12 +1,12 @@
 // SPDX-License-Identifier: MPL-2.0
 
-use alloc::{boxed::Box, collections::VecDeque, sync::Arc, vec::Vec};
+use alloc::{boxed::Box, collections::VecDeque, sync::Arc};
 
 use super::{
     info::CommonSchedInfo, inject_scheduler, EnqueueFlags, LocalRunQueue, Scheduler, UpdateFlags,
 };
 use crate::{
-    cpu::{num_cpus, CpuId, PinCurrentCpu},
+    cpu::{local::PerCpu, CpuId, PinCurrentCpu},
     sync::SpinLock,
     task::{disable_preempt, Task},
 };
@@ -20,17 +20,15 @@ pub fn init() {
 /// A simple FIFO (First-In-First-Out) task scheduler.
 struct FifoScheduler<T: CommonSchedInfo> {
     /// A thread-safe queue to hold tasks waiting to be executed.
-    rq: Vec<SpinLock<FifoRunQueue<T>>>,
+    rq: PerCpu<SpinLock<FifoRunQueue<T>>>,
 }
 
 impl<T: CommonSchedInfo> FifoScheduler<T> {
     /// Creates a new instance of `FifoScheduler`.
-    fn new(nr_cpus: usize) -> Self {
-        let mut rq = Vec::new();
-        for _ in 0..nr_cpus {
-            rq.push(SpinLock::new(FifoRunQueue::new()));
+    fn new() -> Self {
+        Self {
+            rq: PerCpu::new(|_| SpinLock::new(FifoRunQueue::new())),
         }
-        Self { rq }
     }
 
     fn select_cpu(&self) -> CpuId {
@@ -52,7 +50,7 @@ impl<T: CommonSchedInfo + Send + Sync> Scheduler<T> for FifoScheduler<T> {
             }
         };
 
-        let mut rq = self.rq[target_cpu.as_usize()].disable_irq().lock();
+        let mut rq = self.rq.get_on_cpu(target_cpu).disable_irq().lock();
         if still_in_rq && let Err(_) = runnable.cpu().set_if_is_none(target_cpu) {
             return None;
         }
@@ -64,7 +62,9 @@ impl<T: CommonSchedInfo + Send + Sync> Scheduler<T> for FifoScheduler<T> {
 
     fn local_rq_with(&self, f: &mut dyn FnMut(&dyn LocalRunQueue<T>)) {
         let preempt_guard = disable_preempt();
-        let local_rq: &FifoRunQueue<T> = &self.rq[preempt_guard.current_cpu().as_usize()]
+        let local_rq: &FifoRunQueue<T> = &self
+            .rq
+            .get_on_cpu(preempt_guard.current_cpu())
             .disable_irq()
             .lock();
         f(local_rq);
@@ -72,7 +72,9 @@ impl<T: CommonSchedInfo + Send + Sync> Scheduler<T> for FifoScheduler<T> {
 
     fn local_mut_rq_with(&self, f: &mut dyn FnMut(&mut dyn LocalRunQueue<T>)) {
         let preempt_guard = disable_preempt();
-        let local_rq: &mut FifoRunQueue<T> = &mut self.rq[preempt_guard.current_cpu().as_usize()]
+        let local_rq: &mut FifoRunQueue<T> = &mut self
+            .rq
+            .get_on_cpu(preempt_guard.current_cpu())
             .disable_irq()
             .lock();
         f(local_rq);
@@ -118,6 +120,6 @@ impl<T: CommonSchedInfo> LocalRunQueue<T> for FifoRunQueue<T> {
 
 impl Default for FifoScheduler<Task> {
     fn default() -> Self {
-        Self::new(num_cpus())
+        Self::new()
     }
 }
@@ -50,6 +50,32 @@ pub trait Scheduler<T = Task>: Sync + Send {
 
     /// Gets a mutable access to the local runqueue of the current CPU core.
     fn local_mut_rq_with(&self, f: &mut dyn FnMut(&mut dyn LocalRunQueue<T>));
+
+    /// Returns the priority that the owner of a lock should inherit when the task
+    /// blocks on the lock, or `None` if the task has no priority to donate.
+    ///
+    /// A smaller value means a higher priority. The default implementation does
+    /// not support priority inheritance.
+    fn inheritable_priority(&self, _task: &T) -> Option<u8> {
+        None
+    }
+
+    /// Sets the priority that the task inherits from the tasks blocked on the
+    /// locks it owns, or removes the inherited priority if `priority` is `None`.
+    ///
+    /// The priority is returned by [`Self::inheritable_priority`].
+    fn set_inherited_priority(&self, _task: &T, _priority: Option<u8>) {}
+
+    /// Moves the runnable tasks away from a CPU that is going offline.
+    ///
+    /// This is called after the CPU is removed from the [`online_cpus`]. From
+    /// then on, the scheduler should not put any tasks onto the CPU, unless a
+    /// task cannot run on any online CPU. The current task of the CPU will be
+    /// preempted afterwards, and it should be moved away as well when it is
+    /// switched out.
+    ///
+    /// [`online_cpus`]: crate::cpu::hotplug::online_cpus
+    fn offline_cpu(&self, _cpu: CpuId) {}
 }
 
 /// The _local_ view of a per-CPU runqueue.
@@ -167,6 +193,26 @@ pub(crate) fn unpark_target(runnable: Arc<Task>) {
     }
 }
 
+/// Returns the priority that the task donates to the owners of the locks it
+/// blocks on.
+pub(crate) fn inheritable_priority(task: &Task) -> Option<u8> {
+    SCHEDULER.get()?.inheritable_priority(task)
+}
+
+/// Sets the priority that the task inherits from the waiters of its locks.
+pub(crate) fn set_inherited_priority(task: &Task, priority: Option<u8>) {
+    if let Some(scheduler) = SCHEDULER.get() {
+        scheduler.set_inherited_priority(task, priority);
+    }
+}
+
+/// Moves the runnable tasks away from a CPU that is going offline.
+pub(crate) fn offline_cpu(cpu_id: CpuId) {
+    if let Some(scheduler) = SCHEDULER.get() {
+        scheduler.offline_cpu(cpu_id);
+    }
+}
+
 /// Enqueues a newly built task.
 ///
 /// Note that the new task is not guaranteed to run at once.
@@ -189,16 +235,66 @@ pub(super) fn run_new_task(runnable: Arc<Task>) {
     might_preempt();
 }
 
-fn set_need_preempt(cpu_id: CpuId) {
+/// Requests the CPU to preempt its current task.
+pub(crate) fn set_need_preempt(cpu_id: CpuId) {
     let preempt_guard = disable_preempt();
 
     if preempt_guard.current_cpu() == cpu_id {
         cpu_local::set_need_preempt();
     } else {
-        // TODO: Send IPIs to set remote CPU's `need_preempt`
+        // The IPI also wakes up the remote CPU if it is idle.
+        crate::smp::inter_processor_call(&cpu_id.into(), cpu_local::set_need_preempt);
     }
 }
 
+/// Halts the current CPU until an interrupt arrives, with its tick stopped.
+///
+/// This function should be called by the idle task of the current CPU when
+/// there are no other runnable tasks. It returns immediately if the current
+/// task needs to be preempted.
+///
+/// The CPU polls for new work for a while before it halts. See
+/// [`crate::cpu::idle`] for details.
+pub fn idle_current_cpu() {
+    crate::sync::rcu::pass_quiescent_state();
+
+    let idle_start_ns = timer::monotonic_ns();
+    {
+        let preempt_guard = disable_preempt();
+        if crate::cpu::idle::poll(&preempt_guard, cpu_local::need_preempt) {
+            return;
+        }
+    }
+
+    let irq_guard = crate::trap::disable_local();
+    if cpu_local::need_preempt() {
+        return;
+    }
+    if !crate::sync::rcu::enter_idle() {
+        crate::sync::rcu::exit_idle();
+        return;
+    }
+
+    // If the current CPU is going offline, the idle task is the only one left to run.
+    if crate::cpu::hotplug::park_current_cpu_if_offline(&irq_guard) {
+        crate::sync::rcu::exit_idle();
+        return;
+    }
+
+    crate::cpu::freq::sample(&irq_guard);
+    let wakeup_ns = timer::nohz::stop_tick(&irq_guard);
+    super::watchdog::enter_idle();
+    // The interrupt has been handled when this returns with local IRQs
+    // disabled. Restart the tick.
+    crate::cpu::idle::enter(&irq_guard, wakeup_ns);
+    super::watchdog::touch();
+    timer::nohz::restart_tick(&irq_guard);
+    crate::sync::rcu::exit_idle();
+
+    let idle_ns = timer::monotonic_ns().saturating_sub(idle_start_ns);
+    crate::cpu::idle::adjust_poll_limit(&irq_guard, idle_ns);
+}
+
 /// Dequeues the current task from its runqueue.
 ///
 /// This should only be called if the current is to exit.
@@ -224,6 +320,8 @@ pub(super) fn yield_now() {
         if let Some(next_task) = local_rq.pick_next_current() {
             ReschedAction::SwitchTo(next_task.clone())
         } else {
+            // There is no other task to run, so the preemption is done.
+            cpu_local::clear_need_preempt();
             ReschedAction::DoNothing
         }
     })
@@ -238,6 +336,9 @@ fn reschedule<F>(mut f: F)
 where
     F: FnMut(&mut dyn LocalRunQueue) -> ReschedAction,
 {
+    // Reaching a scheduling point means that the CPU is not soft-locked up.
+    super::watchdog::touch();
+
     let next_task = loop {
         let mut action = ReschedAction::DoNothing;
         SCHEDULER.get().unwrap().local_mut_rq_with(&mut |rq| {
@@ -261,6 +362,10 @@ where
     // CPU core. However, we currently have no way to ensure this. This is a soundness hole and
     // should be fixed. See <https://github.com/asterinas/asterinas/issues/1471> for details.
 
+    // The current task cannot be in any RCU read-side critical sections, since
+    // they disable preemption.
+    crate::sync::rcu::pass_quiescent_state();
+
     cpu_local::clear_need_preempt();
     processor::switch_to_task(next_task);
 }
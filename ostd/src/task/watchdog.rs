@@ -0,0 +1,143 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! The soft-lockup detector.
+//!
+//! A CPU is soft-locked up if it keeps running the kernel code without scheduling for a long
+//! time, e.g., because of spinning on a lock that is never released. Since the kernel code is
+//! not preempted, no other tasks can run on the CPU in the meantime.
+//!
+//! Each CPU records the time when it schedules, becomes idle, or is interrupted in the user
+//! mode. The timer interrupt checks whether the time is older than the threshold, and reports
+//! the call chain of the interrupted kernel code if so.
+//!
+//! The detector cannot find the lockups with the local IRQs disabled (the hard lockups), since
+//! the timer interrupt is not handled then. It is also limited to the architectures where the
+//! timer interrupt is handled, which currently excludes RISC-V.
+
+use core::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+
+use crate::{
+    arch::trap::{is_kernel_interrupted, walk_kernel_stack, TrapFrame},
+    cpu::PinCurrentCpu,
+    cpu_local_cell, early_println,
+    panic::lookup_symbol,
+    timer::monotonic_ns,
+    trap,
+};
+
+/// The threshold of the soft lockups in seconds, or zero if the detector is disabled.
+static THRESHOLD_SECS: AtomicU64 = AtomicU64::new(20);
+
+static PANIC_ON_LOCKUP: AtomicBool = AtomicBool::new(false);
+
+/// The value of `TOUCHED_NS` if the CPU is idle.
+const IDLE: u64 = u64::MAX;
+
+cpu_local_cell! {
+    /// The monotonic time in nanoseconds when the CPU is known to make progress.
+    ///
+    /// Zero means that the time is not recorded yet.
+    static TOUCHED_NS: u64 = 0;
+    /// Whether the current lockup has been reported.
+    static IS_REPORTED: bool = false;
+}
+
+/// Returns the threshold of the soft lockups in seconds.
+///
+/// Zero means that the detector is disabled.
+pub fn threshold_secs() -> u64 {
+    THRESHOLD_SECS.load(Ordering::Relaxed)
+}
+
+/// Sets the threshold of the soft lockups in seconds.
+///
+/// Setting it to zero disables the detector.
+pub fn set_threshold_secs(secs: u64) {
+    THRESHOLD_SECS.store(secs, Ordering::Relaxed);
+}
+
+/// Returns whether the kernel panics when a soft lockup is detected.
+pub fn panic_on_lockup() -> bool {
+    PANIC_ON_LOCKUP.load(Ordering::Relaxed)
+}
+
+/// Sets whether the kernel panics when a soft lockup is detected.
+pub fn set_panic_on_lockup(panic_on_lockup: bool) {
+    PANIC_ON_LOCKUP.store(panic_on_lockup, Ordering::Relaxed);
+}
+
+/// Records that the current CPU makes progress.
+///
+/// Legitimate long-running kernel operations that never schedule can call this function to
+/// avoid being reported as soft lockups.
+pub fn touch() {
+    TOUCHED_NS.store(monotonic_ns());
+    IS_REPORTED.store(false);
+}
+
+/// Records that the current CPU becomes idle.
+///
+/// The idle CPU is not checked until it is touched again.
+pub(crate) fn enter_idle() {
+    TOUCHED_NS.store(IDLE);
+    IS_REPORTED.store(false);
+}
+
+/// Checks whether the current CPU is soft-locked up.
+///
+/// This function should be called in the timer interrupt handler.
+pub(crate) fn check(trap_frame: &TrapFrame) {
+    let threshold_secs = threshold_secs();
+    if threshold_secs == 0 {
+        return;
+    }
+    if !is_kernel_interrupted() {
+        touch();
+        return;
+    }
+
+    let touched_ns = TOUCHED_NS.load();
+    if touched_ns == IDLE {
+        return;
+    }
+    let now_ns = monotonic_ns();
+    if touched_ns == 0 {
+        TOUCHED_NS.store(now_ns);
+        return;
+    }
+
+    let stuck_secs = now_ns.saturating_sub(touched_ns) / 1_000_000_000;
+    if stuck_secs < threshold_secs || IS_REPORTED.load() {
+        return;
+    }
+    IS_REPORTED.store(true);
+
+    let irq_guard = trap::disable_local();
+    early_println!(
+        "BUG: soft lockup - CPU#{} stuck for {}s!",
+        irq_guard.current_cpu().as_usize(),
+        stuck_secs
+    );
+    early_println!("Call trace:");
+    let mut is_first = true;
+    walk_kernel_stack(trap_frame, |addr| {
+        // The addresses other than the first one are return addresses. Look up the address
+        // of the call instruction, in case that the call is the last instruction of a function.
+        let lookup_addr = if is_first { addr } else { addr - 1 };
+        match lookup_symbol(lookup_addr) {
+            Some((name, offset)) => early_println!(
+                "  {:#18x} <{}+{:#x}>",
+                addr,
+                name,
+                offset + (addr - lookup_addr)
+            ),
+            None => early_println!("  {:#18x}", addr),
+        }
+        is_first = false;
+        true
+    });
+
+    if panic_on_lockup() {
+        panic!("softlockup: hung tasks");
+    }
+}
@@ -0,0 +1,245 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! High-resolution timers.
+//!
+//! Each CPU has a queue of armed [`HrTimer`]s ordered by their expiration times.
+//! The architectural timer of a CPU is programmed in one-shot mode to interrupt
+//! at the earliest expiration time in its queue, so the expiration times are not
+//! rounded to the system ticks.
+//!
+//! If the architectural timer can only work in the periodic mode, the timers
+//! are checked on every tick instead.
+
+use alloc::{boxed::Box, collections::BTreeMap, sync::Arc};
+use core::{
+    sync::atomic::{AtomicU64, Ordering},
+    time::Duration,
+};
+
+use crate::{
+    arch,
+    cpu::{CpuId, PinCurrentCpu},
+    cpu_local,
+    sync::{LocalIrqDisabled, SpinLock},
+    trap::{self, DisabledLocalIrqGuard},
+};
+
+/// Returns the monotonic time since the system boots up in nanoseconds.
+pub fn monotonic_ns() -> u64 {
+    let freq = arch::tsc_freq();
+    if freq == 0 {
+        return 0;
+    }
+    (arch::read_tsc() as u128 * 1_000_000_000 / freq as u128) as u64
+}
+
+/// The key of an armed timer in a timer queue.
+///
+/// The key consists of the expiration time and a unique sequence number, so
+/// that timers with the same expiration time are ordered by their arming time.
+type QueueKey = (u64, u64);
+
+static NEXT_SEQ: AtomicU64 = AtomicU64::new(0);
+
+cpu_local! {
+    static TIMER_QUEUES: SpinLock<BTreeMap<QueueKey, Arc<TimerInner>>, LocalIrqDisabled> =
+        SpinLock::new(BTreeMap::new());
+}
+
+/// A high-resolution one-shot timer.
+///
+/// A timer is armed on the queue of the current CPU, and its callback is executed
+/// in the interrupt context of that CPU once the expiration time is reached. The
+/// callback must not sleep.
+///
+/// Dropping a timer cancels it.
+pub struct HrTimer {
+    inner: Arc<TimerInner>,
+}
+
+struct TimerInner {
+    callback: Box<dyn Fn() + Send + Sync>,
+    /// The CPU and the queue key of the timer if it is armed.
+    ///
+    /// Lock ordering: lock `slot` before `TIMER_QUEUES`.
+    slot: SpinLock<Option<(CpuId, QueueKey)>, LocalIrqDisabled>,
+}
+
+impl HrTimer {
+    /// Creates a new timer with the callback to execute on expiration.
+    pub fn new<F>(callback: F) -> Self
+    where
+        F: Fn() + Send + Sync + 'static,
+    {
+        Self {
+            inner: Arc::new(TimerInner {
+                callback: Box::new(callback),
+                slot: SpinLock::new(None),
+            }),
+        }
+    }
+
+    /// Arms the timer to expire at `expires_ns`, which is a [`monotonic_ns`] value.
+    ///
+    /// If the timer is already armed, it is re-armed with the new expiration time
+    /// on the current CPU. If `expires_ns` has already passed, the timer expires
+    /// as soon as possible.
+    pub fn arm_at(&self, expires_ns: u64) {
+        let irq_guard = trap::disable_local();
+        let cpu = irq_guard.current_cpu();
+
+        let mut slot = self.inner.slot.lock();
+        if let Some((old_cpu, old_key)) = slot.take() {
+            TIMER_QUEUES.get_on_cpu(old_cpu).lock().remove(&old_key);
+        }
+
+        let key = (expires_ns, NEXT_SEQ.fetch_add(1, Ordering::Relaxed));
+        let is_earliest = {
+            let mut queue = TIMER_QUEUES.get_on_cpu(cpu).lock();
+            queue.insert(key, self.inner.clone());
+            queue.first_key_value().unwrap().0 == &key
+        };
+        *slot = Some((cpu, key));
+        drop(slot);
+
+        if is_earliest {
+            arch::timer::program_next_event(&irq_guard);
+        }
+    }
+
+    /// Arms the timer to expire after `timeout` from now.
+    pub fn arm_after(&self, timeout: Duration) {
+        let timeout_ns = u64::try_from(timeout.as_nanos()).unwrap_or(u64::MAX);
+        self.arm_at(monotonic_ns().saturating_add(timeout_ns));
+    }
+
+    /// Cancels the timer.
+    ///
+    /// Returns `true` if the timer was armed and its callback will not be executed.
+    /// Returns `false` if the timer was not armed, or its callback is being
+    /// executed or has been executed.
+    pub fn cancel(&self) -> bool {
+        let mut slot = self.inner.slot.lock();
+        let Some((cpu, key)) = slot.take() else {
+            return false;
+        };
+        TIMER_QUEUES.get_on_cpu(cpu).lock().remove(&key);
+        true
+    }
+
+    /// Returns whether the timer is armed.
+    pub fn is_armed(&self) -> bool {
+        self.inner.slot.lock().is_some()
+    }
+
+    /// Returns the expiration time of the timer if it is armed.
+    pub fn expires_ns(&self) -> Option<u64> {
+        self.inner
+            .slot
+            .lock()
+            .map(|(_, (expires_ns, _))| expires_ns)
+    }
+}
+
+impl Drop for HrTimer {
+    fn drop(&mut self) {
+        self.cancel();
+    }
+}
+
+impl core::fmt::Debug for HrTimer {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("HrTimer")
+            .field("expires_ns", &self.expires_ns())
+            .finish_non_exhaustive()
+    }
+}
+
+/// Returns the earliest expiration time of the timers armed on the current CPU.
+pub(crate) fn next_expiration(guard: &DisabledLocalIrqGuard) -> Option<u64> {
+    let queue = TIMER_QUEUES.get_on_cpu(guard.current_cpu()).lock();
+    queue
+        .first_key_value()
+        .map(|((expires_ns, _), _)| *expires_ns)
+}
+
+/// Executes the callbacks of the expired timers on the current CPU.
+///
+/// This function should be called in the timer interrupt handler.
+pub(crate) fn expire_timers(guard: &DisabledLocalIrqGuard) {
+    let cpu = guard.current_cpu();
+    let queue = TIMER_QUEUES.get_on_cpu(cpu);
+
+    loop {
+        let now = monotonic_ns();
+        let expired = {
+            let mut queue = queue.lock();
+            match queue.first_key_value() {
+                Some(((expires_ns, _), _)) if *expires_ns <= now => queue.pop_first(),
+                _ => None,
+            }
+        };
+        let Some((key, timer)) = expired else {
+            break;
+        };
+
+        // The timer may have been canceled or re-armed after being removed from
+        // the queue. The callback is executed only if the slot is still ours.
+        let mut slot = timer.slot.lock();
+        if *slot != Some((cpu, key)) {
+            continue;
+        }
+        *slot = None;
+        drop(slot);
+
+        (timer.callback)();
+    }
+}
+
+#[cfg(ktest)]
+mod test {
+    use alloc::vec::Vec;
+    use core::sync::atomic::AtomicBool;
+
+    use ostd_macros::ktest;
+
+    use super::*;
+
+    #[ktest]
+    fn cancel_armed_timer() {
+        let fired = Arc::new(AtomicBool::new(false));
+        let timer = {
+            let fired = fired.clone();
+            HrTimer::new(move || fired.store(true, Ordering::Relaxed))
+        };
+
+        assert!(!timer.cancel());
+        timer.arm_after(Duration::from_secs(3600));
+        assert!(timer.is_armed());
+        assert!(timer.cancel());
+        assert!(!timer.is_armed());
+        assert!(!fired.load(Ordering::Relaxed));
+    }
+
+    #[ktest]
+    fn expire_timers_in_order() {
+        let order: Arc<SpinLock<Vec<u32>, LocalIrqDisabled>> = Arc::new(SpinLock::new(Vec::new()));
+        let new_timer = |id| {
+            let order = order.clone();
+            HrTimer::new(move || order.lock().push(id))
+        };
+        let late = new_timer(2);
+        let early = new_timer(1);
+
+        let now = monotonic_ns();
+        late.arm_at(now + 2);
+        early.arm_at(now + 1);
+        while monotonic_ns() <= now + 2 {
+            core::hint::spin_loop();
+        }
+        expire_timers(&trap::disable_local());
+
+        assert_eq!(*order.lock(), [1, 2]);
+        assert!(!early.is_armed() && !late.is_armed());
+    }
+}
@@ -2,14 +2,22 @@
 
 //! The timer support.
 
+pub(crate) mod hrtimer;
 pub(crate) mod jiffies;
+pub(crate) mod nohz;
 
 use alloc::{boxed::Box, vec::Vec};
 use core::cell::RefCell;
 
+pub use hrtimer::{monotonic_ns, HrTimer};
 pub use jiffies::Jiffies;
+pub use nohz::register_next_event_hint;
+use spin::Once;
 
-use crate::{cpu_local, trap};
+use crate::{
+    cpu_local,
+    trap::{self, TrapFrame},
+};
 
 type InterruptCallback = Box<dyn Fn() + Sync + Send>;
 
@@ -28,3 +36,20 @@ where
         .borrow_mut()
         .push(Box::new(func));
 }
+
+static SAMPLE_CALLBACK: Once<fn(&TrapFrame)> = Once::new();
+
+/// Registers a function that samples the interrupted code on every timer interruption.
+///
+/// The function is called on every CPU in the interrupt context, with the trap frame of
+/// the code that is interrupted in either the kernel mode or the user mode. It is meant
+/// for sampling profilers. Only one function can be registered.
+pub fn register_sample_callback(func: fn(&TrapFrame)) {
+    SAMPLE_CALLBACK.call_once(|| func);
+}
+
+pub(crate) fn call_sample_callback(trap_frame: &TrapFrame) {
+    if let Some(callback) = SAMPLE_CALLBACK.get() {
+        callback(trap_frame);
+    }
+}
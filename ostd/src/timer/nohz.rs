@@ -0,0 +1,63 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! Tickless idle.
+//!
+//! When a CPU becomes idle, its periodic tick is stopped. The timer of the CPU
+//! is then programmed for the next event that needs to be handled, i.e., the
+//! earliest one of the expirations of the high-resolution timers and the events
+//! reported by the registered hints, such as the expirations of the timers that
+//! are processed on ticks.
+
+use alloc::{boxed::Box, vec::Vec};
+use core::time::Duration;
+
+use super::hrtimer::{self, monotonic_ns};
+use crate::{
+    arch,
+    sync::{LocalIrqDisabled, RwLock},
+    trap::DisabledLocalIrqGuard,
+};
+
+type NextEventHint = Box<dyn Fn() -> Option<Duration> + Send + Sync>;
+
+static NEXT_EVENT_HINTS: RwLock<Vec<NextEventHint>, LocalIrqDisabled> = RwLock::new(Vec::new());
+
+/// The maximum time for which the tick is stopped, in nanoseconds.
+///
+/// This bounds the latency of the work that relies on ticks but does not
+/// report its next event with a hint.
+const MAX_STOPPED_NS: u64 = 1_000_000_000;
+
+/// Registers a function that returns the time until the next event that relies
+/// on the system ticks, or `None` if there is no such event.
+///
+/// The function is called with local IRQs disabled when a CPU becomes idle.
+pub fn register_next_event_hint<F>(hint: F)
+where
+    F: Fn() -> Option<Duration> + Send + Sync + 'static,
+{
+    NEXT_EVENT_HINTS.write().push(Box::new(hint));
+}
+
+/// Stops the tick of the current CPU until the next event.
+///
+/// Returns the time of the next event, including the expirations of the
+/// high-resolution timers, which the idle CPU is expected to be woken up at.
+pub(crate) fn stop_tick(guard: &DisabledLocalIrqGuard) -> u64 {
+    let now = monotonic_ns();
+    let timeout_ns = NEXT_EVENT_HINTS
+        .read()
+        .iter()
+        .filter_map(|hint| hint())
+        .map(|timeout| u64::try_from(timeout.as_nanos()).unwrap_or(u64::MAX))
+        .fold(MAX_STOPPED_NS, u64::min);
+    let until_ns = now.saturating_add(timeout_ns);
+    arch::timer::stop_tick(guard, until_ns);
+
+    hrtimer::next_expiration(guard).map_or(until_ns, |expires_ns| expires_ns.min(until_ns))
+}
+
+/// Restarts the tick of the current CPU.
+pub(crate) fn restart_tick(guard: &DisabledLocalIrqGuard) {
+    arch::timer::restart_tick(guard);
+}
@@ -59,6 +59,9 @@ pub(crate) fn call_irq_callback_functions(trap_frame: &TrapFrame, irq_number: us
     // bottom half cannot be reentrant for the same reason.
     INTERRUPT_NESTED_LEVEL.add_assign(1);
 
+    // The interrupt may arrive while the CPU is idle.
+    crate::sync::rcu::exit_idle();
+
     process_top_half(trap_frame, irq_number);
     crate::arch::interrupts_ack(irq_number);
 
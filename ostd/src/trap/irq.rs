@@ -5,10 +5,12 @@
 use core::fmt::Debug;
 
 use crate::{
-    arch::irq::{self, IrqCallbackHandle, IRQ_ALLOCATOR},
+    arch::irq::{self, IrqCallbackHandle, IRQ_ALLOCATOR, IRQ_LIST},
+    cpu::{hotplug, CpuId},
     prelude::*,
-    sync::GuardTransfer,
-    trap::TrapFrame,
+    sync::{GuardTransfer, Mutex},
+    task::atomic_mode::might_sleep,
+    trap::{IrqReturn, IrqThread, TrapFrame},
     Error,
 };
 
@@ -76,6 +78,61 @@ impl IrqLine {
         self.callbacks.push(self.inner_irq.on_active(callback))
     }
 
+    /// Registers a threaded handler that will be invoked when the IRQ is active.
+    ///
+    /// The primary handler is invoked in the interrupt context, just like the
+    /// callbacks registered with [`Self::on_active`]. If it returns
+    /// [`IrqReturn::WakeThread`], `thread_fn` will be executed later in a
+    /// dedicated kernel thread, where interrupts are enabled and sleeping is
+    /// allowed. See [`IrqThread`] for details.
+    pub fn on_active_threaded<F, G>(&mut self, primary: F, thread_fn: G)
+    where
+        F: Fn(&TrapFrame) -> IrqReturn + Sync + Send + 'static,
+        G: Fn() + Sync + Send + 'static,
+    {
+        let irq_thread = IrqThread::new(thread_fn);
+        self.on_active(move |trap_frame| {
+            if primary(trap_frame) == IrqReturn::WakeThread {
+                irq_thread.wake();
+            }
+        });
+    }
+
+    /// Returns the CPU that the IRQ is delivered to.
+    ///
+    /// By default, IRQs are delivered to the bootstrap processor (BSP).
+    pub fn affinity(&self) -> CpuId {
+        self.inner_irq.affinity()
+    }
+
+    /// Delivers the IRQ to a CPU.
+    ///
+    /// A driver with per-CPU queues can deliver the IRQ of each queue to the CPU that
+    /// processes the queue, so that the CPU receiving the IRQ does not need to notify
+    /// another CPU with an IPI. The affinity can be set either before or after the IRQ
+    /// line is connected to an interrupt source, e.g., an I/O APIC pin or an MSI-X
+    /// table entry.
+    ///
+    /// If the CPU goes offline later, the IRQ is delivered to the BSP instead.
+    ///
+    /// # Errors
+    ///
+    /// This method returns [`Error::InvalidArgs`] if the CPU is offline or cannot be
+    /// the destination of the interrupt source.
+    ///
+    /// # Panics
+    ///
+    /// This method panics if it is called in atomic mode.
+    pub fn set_affinity(&self, cpu_id: CpuId) -> Result<()> {
+        might_sleep();
+        let _guard = AFFINITY_LOCK.lock();
+
+        if !hotplug::is_online(cpu_id) {
+            return Err(Error::InvalidArgs);
+        }
+        self.inner_irq.set_affinity(cpu_id)
+    }
+
     /// Checks if there are no registered callbacks.
     pub fn is_empty(&self) -> bool {
         self.callbacks.is_empty()
@@ -99,6 +156,7 @@ impl Clone for IrqLine {
 impl Drop for IrqLine {
     fn drop(&mut self) {
         if Arc::strong_count(&self.inner_irq) == 1 {
+            self.inner_irq.reset_affinity();
             IRQ_ALLOCATOR
                 .get()
                 .unwrap()
@@ -108,6 +166,23 @@ impl Drop for IrqLine {
     }
 }
 
+/// Serializes the changes to the affinities of the IRQ lines.
+static AFFINITY_LOCK: Mutex<()> = Mutex::new(());
+
+/// Delivers the IRQs that are delivered to an offline CPU to the BSP instead.
+pub(crate) fn migrate_irqs_away_from(cpu_id: CpuId) {
+    let _guard = AFFINITY_LOCK.lock();
+
+    for irq in IRQ_LIST.get().unwrap().iter() {
+        if irq.affinity() != cpu_id {
+            continue;
+        }
+        if let Err(err) = irq.set_affinity(CpuId::bsp()) {
+            log::warn!("Failed to migrate IRQ {} away: {:?}", irq.num(), err);
+        }
+    }
+}
+
 /// Disables all IRQs on the current CPU (i.e., locally).
 ///
 /// This function returns a guard object, which will automatically enable local IRQs again when
@@ -167,3 +242,18 @@ impl Drop for DisabledLocalIrqGuard {
         }
     }
 }
+
+#[cfg(ktest)]
+mod test {
+    use ostd_macros::ktest;
+
+    use super::*;
+
+    #[ktest]
+    fn set_affinity_to_bsp() {
+        let irq = IrqLine::alloc().unwrap();
+        assert_eq!(irq.affinity(), CpuId::bsp());
+        irq.set_affinity(CpuId::bsp()).unwrap();
+        assert_eq!(irq.clone().affinity(), CpuId::bsp());
+    }
+}
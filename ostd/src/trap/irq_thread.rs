@@ -0,0 +1,193 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! Threaded IRQ handlers.
+//!
+//! A threaded IRQ handler consists of two parts. The primary handler runs in
+//! the interrupt context, where it should only acknowledge the device and
+//! decide whether there is more work to do. The thread function does the rest
+//! of the work in a dedicated kernel thread, where interrupts are enabled and
+//! sleeping is allowed.
+//!
+//! OSTD does not know how the kernel creates its threads, so the kernel should
+//! inject an [`IrqThreadSpawner`] with [`inject_irq_thread_spawner`]. The IRQ
+//! threads requested before the injection are spawned at the injection, and
+//! the events that arrive in the meantime are not lost.
+
+use core::sync::atomic::{AtomicBool, Ordering};
+
+use crate::{
+    prelude::*,
+    sync::{SpinLock, WaitQueue},
+};
+
+/// The function that spawns a kernel thread to run the given function.
+pub type IrqThreadSpawner = fn(Box<dyn Fn() + Send + Sync>);
+
+/// The return value of the primary handler of a threaded IRQ handler.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IrqReturn {
+    /// The interrupt is fully handled.
+    Handled,
+    /// The thread function should be executed for the interrupt.
+    WakeThread,
+}
+
+/// A kernel thread that executes a function whenever it is woken.
+///
+/// The wake-ups that arrive while the function is executing are coalesced, so
+/// that the function is executed once more after the current execution.
+pub struct IrqThread {
+    inner: Arc<IrqThreadInner>,
+}
+
+struct IrqThreadInner {
+    thread_fn: Box<dyn Fn() + Send + Sync>,
+    is_pending: AtomicBool,
+    is_stopped: AtomicBool,
+    wait_queue: WaitQueue,
+}
+
+impl IrqThread {
+    /// Creates a new IRQ thread that executes `thread_fn` whenever it is woken.
+    ///
+    /// The thread exits when the returned object is dropped.
+    pub fn new<F>(thread_fn: F) -> Self
+    where
+        F: Fn() + Send + Sync + 'static,
+    {
+        let inner = Arc::new(IrqThreadInner {
+            thread_fn: Box::new(thread_fn),
+            is_pending: AtomicBool::new(false),
+            is_stopped: AtomicBool::new(false),
+            wait_queue: WaitQueue::new(),
+        });
+        spawn_or_defer(inner.clone());
+        Self { inner }
+    }
+
+    /// Wakes the thread to execute the function.
+    ///
+    /// This method can be called in the interrupt context.
+    pub fn wake(&self) {
+        self.inner.is_pending.store(true, Ordering::Release);
+        self.inner.wait_queue.wake_one();
+    }
+}
+
+impl Drop for IrqThread {
+    fn drop(&mut self) {
+        self.inner.is_stopped.store(true, Ordering::Release);
+        self.inner.wait_queue.wake_all();
+    }
+}
+
+impl IrqThreadInner {
+    fn run(&self) {
+        loop {
+            self.wait_queue.wait_until(|| {
+                let is_pending = self.is_pending.swap(false, Ordering::AcqRel);
+                (is_pending || self.is_stopped.load(Ordering::Acquire)).then_some(())
+            });
+            if self.is_stopped.load(Ordering::Acquire) {
+                return;
+            }
+            (self.thread_fn)();
+        }
+    }
+}
+
+struct SpawnState {
+    spawner: Option<IrqThreadSpawner>,
+    deferred: Vec<Arc<IrqThreadInner>>,
+}
+
+static SPAWN_STATE: SpinLock<SpawnState> = SpinLock::new(SpawnState {
+    spawner: None,
+    deferred: Vec::new(),
+});
+
+/// Injects the function that spawns the IRQ threads.
+///
+/// This function can only be called once. Subsequent calls will do nothing.
+pub fn inject_irq_thread_spawner(spawner: IrqThreadSpawner) {
+    let deferred = {
+        let mut state = SPAWN_STATE.lock();
+        if state.spawner.is_some() {
+            return;
+        }
+        state.spawner = Some(spawner);
+        core::mem::take(&mut state.deferred)
+    };
+
+    for inner in deferred {
+        spawner(Box::new(move || inner.run()));
+    }
+}
+
+fn spawn_or_defer(inner: Arc<IrqThreadInner>) {
+    let spawner = {
+        let mut state = SPAWN_STATE.lock();
+        let Some(spawner) = state.spawner else {
+            state.deferred.push(inner);
+            return;
+        };
+        spawner
+    };
+
+    spawner(Box::new(move || inner.run()));
+}
+
+#[cfg(ktest)]
+mod test {
+    use core::sync::atomic::AtomicUsize;
+
+    use super::*;
+    use crate::{
+        arch::irq::is_local_enabled,
+        task::{Task, TaskOptions},
+        trap::{disable_local, in_interrupt_context},
+    };
+
+    fn spawn_task(thread_fn: Box<dyn Fn() + Send + Sync>) {
+        TaskOptions::new(move || thread_fn())
+            .data(())
+            .spawn()
+            .unwrap();
+    }
+
+    #[ktest]
+    fn thread_fn_runs_in_task() {
+        // The thread is spawned at the injection if it is requested before.
+        let nr_runs = Arc::new(AtomicUsize::new(0));
+        let is_in_task_with_irqs = Arc::new(AtomicBool::new(false));
+        let irq_thread = {
+            let nr_runs = nr_runs.clone();
+            let is_in_task_with_irqs = is_in_task_with_irqs.clone();
+            IrqThread::new(move || {
+                is_in_task_with_irqs.store(
+                    !in_interrupt_context() && is_local_enabled(),
+                    Ordering::Relaxed,
+                );
+                nr_runs.fetch_add(1, Ordering::Release);
+            })
+        };
+        inject_irq_thread_spawner(spawn_task);
+
+        // Wake the thread like a primary handler, i.e., with local IRQs disabled.
+        {
+            let _irq_guard = disable_local();
+            irq_thread.wake();
+            irq_thread.wake();
+        }
+        while nr_runs.load(Ordering::Acquire) == 0 {
+            Task::yield_now();
+        }
+        assert!(is_in_task_with_irqs.load(Ordering::Relaxed));
+
+        // The wake-ups before the execution are coalesced.
+        for _ in 0..8 {
+            Task::yield_now();
+        }
+        assert_eq!(nr_runs.load(Ordering::Acquire), 1);
+    }
+}
@@ -4,9 +4,13 @@
 
 mod handler;
 mod irq;
+mod irq_thread;
 
 pub use handler::{in_interrupt_context, register_bottom_half_handler};
 
-pub(crate) use self::handler::call_irq_callback_functions;
-pub use self::irq::{disable_local, DisabledLocalIrqGuard, IrqCallbackFunction, IrqLine};
+pub(crate) use self::{handler::call_irq_callback_functions, irq::migrate_irqs_away_from};
+pub use self::{
+    irq::{disable_local, DisabledLocalIrqGuard, IrqCallbackFunction, IrqLine},
+    irq_thread::{inject_irq_thread_spawner, IrqReturn, IrqThread, IrqThreadSpawner},
+};
 pub use crate::arch::trap::TrapFrame;
@@ -8,6 +8,7 @@ use crate::{
     cpu::{FpuState, UserContext},
     mm::VmSpace,
     prelude::*,
+    sync::SpinLock,
     trap::TrapFrame,
 };
 
@@ -18,7 +19,7 @@ use crate::{
 #[derive(Debug)]
 pub struct UserSpace {
     /// vm space
-    vm_space: Arc<VmSpace>,
+    vm_space: SpinLock<Arc<VmSpace>>,
     /// cpu context before entering user space
     init_ctx: UserContext,
 }
@@ -29,12 +30,25 @@ impl UserSpace {
     /// Each instance maintains a VM address space and the CPU state to enable
     /// execution in the user space.
     pub fn new(vm_space: Arc<VmSpace>, init_ctx: UserContext) -> Self {
-        Self { vm_space, init_ctx }
+        Self {
+            vm_space: SpinLock::new(vm_space),
+            init_ctx,
+        }
     }
 
     /// Returns the VM address space.
-    pub fn vm_space(&self) -> &Arc<VmSpace> {
-        &self.vm_space
+    pub fn vm_space(&self) -> Arc<VmSpace> {
+        self.vm_space.lock().clone()
+    }
+
+    /// Replaces the VM address space and returns the old one.
+    ///
+    /// This method should only be called by the task that is bound to this user space, since
+    /// the new VM address space is activated on the current CPU immediately.
+    pub fn replace_vm_space(&self, vm_space: Arc<VmSpace>) -> Arc<VmSpace> {
+        let mut current_vm_space = self.vm_space.lock();
+        vm_space.activate();
+        core::mem::replace(&mut *current_vm_space, vm_space)
     }
 
     /// Returns the user mode that is bound to the current task and user space.
@@ -0,0 +1,168 @@
+// SPDX-License-Identifier: MPL-2.0
+
+#define _GNU_SOURCE
+
+#include "../network/test.h"
+
+#include <asm/prctl.h>
+#include <sched.h>
+#include <signal.h>
+#include <sys/auxv.h>
+#include <sys/syscall.h>
+#include <sys/wait.h>
+#include <unistd.h>
+
+#ifndef HWCAP2_FSGSBASE
+#define HWCAP2_FSGSBASE (1 << 1)
+#endif
+
+#define KERNEL_ADDR 0xffff800000000000UL
+
+static unsigned long fs_buf[2] = { 0x1111 };
+static unsigned long gs_buf[2] = { 0x2222 };
+static unsigned long gs_buf_child[2] = { 0x3333 };
+static unsigned long gs_buf_signal[2] = { 0x4444 };
+
+// The C library uses the FS base for its thread-local storage, so no library
+// functions (including the `syscall` wrapper, which may set `errno`) can be
+// called while the FS base is changed.
+static long raw_arch_prctl(int code, unsigned long addr)
+{
+	long ret;
+
+	asm volatile("syscall"
+		     : "=a"(ret)
+		     : "a"(SYS_arch_prctl), "D"(code), "S"(addr)
+		     : "rcx", "r11", "memory");
+	return ret;
+}
+
+static unsigned long read_fs_0(void)
+{
+	unsigned long val;
+
+	asm volatile("mov %%fs:0, %0" : "=r"(val));
+	return val;
+}
+
+static unsigned long read_gs_0(void)
+{
+	unsigned long val;
+
+	asm volatile("mov %%gs:0, %0" : "=r"(val));
+	return val;
+}
+
+static long get_base(int code)
+{
+	long base = 0;
+
+	CHECK(syscall(SYS_arch_prctl, code, &base));
+	return base;
+}
+
+FN_TEST(fs_base)
+{
+	unsigned long val;
+	long orig_fs, ret_set, ret_restore;
+
+	orig_fs = get_base(ARCH_GET_FS);
+	TEST_RES(orig_fs, _ret != 0);
+
+	ret_set = raw_arch_prctl(ARCH_SET_FS, (unsigned long)fs_buf);
+	val = read_fs_0();
+	ret_restore = raw_arch_prctl(ARCH_SET_FS, orig_fs);
+
+	TEST_RES(ret_set, _ret == 0);
+	TEST_RES(ret_restore, _ret == 0);
+	TEST_RES(val, _ret == 0x1111);
+	TEST_RES(get_base(ARCH_GET_FS), _ret == orig_fs);
+}
+END_TEST()
+
+FN_TEST(gs_base)
+{
+	TEST_SUCC(syscall(SYS_arch_prctl, ARCH_SET_GS, gs_buf));
+	TEST_RES(get_base(ARCH_GET_GS), _ret == (long)gs_buf);
+	TEST_RES(read_gs_0(), _ret == 0x2222);
+}
+END_TEST()
+
+FN_TEST(invalid_args)
+{
+	TEST_ERRNO(syscall(SYS_arch_prctl, ARCH_SET_FS, KERNEL_ADDR), EPERM);
+	TEST_ERRNO(syscall(SYS_arch_prctl, ARCH_SET_GS, KERNEL_ADDR), EPERM);
+	TEST_ERRNO(syscall(SYS_arch_prctl, ARCH_GET_FS, NULL), EFAULT);
+	TEST_ERRNO(syscall(SYS_arch_prctl, ARCH_GET_GS, NULL), EFAULT);
+	TEST_ERRNO(syscall(SYS_arch_prctl, 0x1005, gs_buf), EINVAL);
+
+	TEST_RES(get_base(ARCH_GET_GS), _ret == (long)gs_buf);
+}
+END_TEST()
+
+FN_TEST(context_switch)
+{
+	int pid, status;
+
+	pid = TEST_SUCC(fork());
+	if (pid == 0) {
+		// The base is inherited from the parent.
+		if (read_gs_0() != 0x2222)
+			_exit(1);
+
+		CHECK(syscall(SYS_arch_prctl, ARCH_SET_GS, gs_buf_child));
+		for (int i = 0; i < 100; i++) {
+			sched_yield();
+			if (read_gs_0() != 0x3333)
+				_exit(2);
+		}
+		_exit(0);
+	}
+
+	for (int i = 0; i < 100; i++)
+		sched_yield();
+	TEST_RES(read_gs_0(), _ret == 0x2222);
+
+	TEST_RES(waitpid(pid, &status, 0),
+		 _ret == pid && WIFEXITED(status) && WEXITSTATUS(status) == 0);
+	TEST_RES(read_gs_0(), _ret == 0x2222);
+}
+END_TEST()
+
+static void set_gs_handler(int sig)
+{
+	(void)sig;
+
+	// Like Linux, the bases are not restored when the handler returns.
+	syscall(SYS_arch_prctl, ARCH_SET_GS, gs_buf_signal);
+}
+
+FN_TEST(signal)
+{
+	TEST_SUCC(signal(SIGUSR1, set_gs_handler) == SIG_ERR ? -1 : 0);
+	TEST_SUCC(raise(SIGUSR1));
+	TEST_RES(read_gs_0(), _ret == 0x4444);
+	TEST_RES(get_base(ARCH_GET_GS), _ret == (long)gs_buf_signal);
+	TEST_SUCC(signal(SIGUSR1, SIG_DFL) == SIG_ERR ? -1 : 0);
+}
+END_TEST()
+
+FN_TEST(fsgsbase_instructions)
+{
+	unsigned long val;
+
+	// The instructions can be used only if they are enabled by the kernel.
+	if (getauxval(AT_HWCAP2) & HWCAP2_FSGSBASE) {
+		// The bases changed by the user space are not lost on context
+		// switches.
+		asm volatile("wrgsbase %0" : : "r"(gs_buf) : "memory");
+		sched_yield();
+		asm volatile("rdgsbase %0" : "=r"(val));
+		TEST_RES(val, _ret == (long)gs_buf);
+		TEST_RES(get_base(ARCH_GET_GS), _ret == (long)gs_buf);
+
+		asm volatile("rdfsbase %0" : "=r"(val));
+		TEST_RES(get_base(ARCH_GET_FS), _ret == (long)val);
+	}
+}
+END_TEST()
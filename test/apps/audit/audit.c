@@ -0,0 +1,413 @@
+// SPDX-License-Identifier: MPL-2.0
+
+#define _GNU_SOURCE
+
+#include "../network/test.h"
+
+#include <fcntl.h>
+#include <linux/audit.h>
+#include <linux/netlink.h>
+#include <stddef.h>
+#include <stdio.h>
+#include <sys/socket.h>
+#include <sys/syscall.h>
+#include <sys/wait.h>
+#include <unistd.h>
+
+#define KEY "audit-test"
+#define USER_MSG "hello from audit test"
+
+static int sk_audit;
+
+#define BUF_SIZE 65536
+static char buf[BUF_SIZE];
+
+struct request {
+	struct nlmsghdr hdr;
+	union {
+		struct audit_status status;
+		struct audit_rule_data rule;
+		char text[64];
+	};
+	char rule_buf[64];
+};
+
+static struct request req;
+
+static int send_request(int sk, size_t payload_len, int type, int flags)
+{
+	static int seq;
+
+	req.hdr.nlmsg_len = NLMSG_LENGTH(payload_len);
+	req.hdr.nlmsg_type = type;
+	req.hdr.nlmsg_flags = NLM_F_REQUEST | flags;
+	req.hdr.nlmsg_seq = ++seq;
+	req.hdr.nlmsg_pid = 0;
+
+	return send(sk, &req, req.hdr.nlmsg_len, 0);
+}
+
+static int found_open, found_path, found_setuid, found_eoe, found_user;
+
+static void check_record(struct nlmsghdr *hdr, size_t len)
+{
+	char *text = NLMSG_DATA(hdr);
+	char syscall[32];
+
+	// Terminate the text, which may not end with a null byte. Note that
+	// Linux does not count the header in the length of the records, so the
+	// length of the datagram is used instead.
+	text[len - NLMSG_HDRLEN] = '\0';
+
+	switch (hdr->nlmsg_type) {
+	case AUDIT_SYSCALL:
+		if (!strstr(text, "key=\"" KEY "\""))
+			break;
+		snprintf(syscall, sizeof(syscall), " syscall=%d ", SYS_openat);
+		if (strstr(text, syscall) && strstr(text, "success=yes"))
+			found_open = 1;
+		snprintf(syscall, sizeof(syscall), " syscall=%d ", SYS_setuid);
+		if (strstr(text, syscall))
+			found_setuid = 1;
+		break;
+	case AUDIT_PATH:
+		if (strstr(text, "name=\"/dev/null\""))
+			found_path = 1;
+		break;
+	case AUDIT_EOE:
+		found_eoe = 1;
+		break;
+	case AUDIT_USER:
+		if (strstr(text, "msg='" USER_MSG "'"))
+			found_user = 1;
+		break;
+	}
+}
+
+// Receives the reply to the last request, and returns its type. The records
+// received before the reply are checked.
+static int recv_reply(int sk, struct nlmsghdr **reply)
+{
+	struct nlmsghdr *hdr = (struct nlmsghdr *)buf;
+	int len;
+
+	for (;;) {
+		len = recv(sk, buf, BUF_SIZE - 1, 0);
+		if (len < 0)
+			return -1;
+		if (!NLMSG_OK(hdr, len))
+			return -1;
+		if (hdr->nlmsg_seq == req.hdr.nlmsg_seq)
+			break;
+		check_record(hdr, len);
+	}
+
+	if (reply)
+		*reply = hdr;
+	return hdr->nlmsg_type;
+}
+
+// Sends a request that requires an acknowledgement, and returns the error code
+// in the acknowledgement.
+static int send_with_ack(int sk, size_t payload_len, int type)
+{
+	struct nlmsghdr *reply;
+	struct nlmsgerr *err;
+
+	if (send_request(sk, payload_len, type, NLM_F_ACK) < 0)
+		return -1;
+	if (recv_reply(sk, &reply) != NLMSG_ERROR)
+		return -1;
+
+	err = NLMSG_DATA(reply);
+	if (err->error != 0) {
+		errno = -err->error;
+		return -1;
+	}
+	return 0;
+}
+
+static int set_status(int sk, __u32 mask, __u32 enabled, __u32 pid)
+{
+	memset(&req, 0, sizeof(req));
+	req.status.mask = mask;
+	req.status.enabled = enabled;
+	req.status.pid = pid;
+
+	return send_with_ack(sk, sizeof(req.status), AUDIT_SET);
+}
+
+static int get_status(struct audit_status *status)
+{
+	struct nlmsghdr *reply;
+
+	memset(&req, 0, sizeof(req));
+	if (send_request(sk_audit, 0, AUDIT_GET, 0) < 0)
+		return -1;
+	if (recv_reply(sk_audit, &reply) != AUDIT_GET)
+		return -1;
+	if (reply->nlmsg_len < NLMSG_LENGTH(offsetof(struct audit_status,
+						      feature_bitmap)))
+		return -1;
+
+	memcpy(status, NLMSG_DATA(reply),
+	       offsetof(struct audit_status, feature_bitmap));
+	return 0;
+}
+
+static void set_syscall(struct audit_rule_data *rule, int nr)
+{
+	rule->mask[nr / 32] |= 1U << (nr % 32);
+}
+
+static void add_field(struct audit_rule_data *rule, __u32 field, __u32 op,
+		      __u32 value)
+{
+	rule->fields[rule->field_count] = field;
+	rule->fieldflags[rule->field_count] = op;
+	rule->values[rule->field_count] = value;
+	rule->field_count++;
+}
+
+// Builds the rule that audits `openat` and `setuid` in the child processes.
+static size_t build_rule(void)
+{
+	struct audit_rule_data *rule = &req.rule;
+
+	memset(&req, 0, sizeof(req));
+	rule->flags = AUDIT_FILTER_EXIT;
+	rule->action = AUDIT_ALWAYS;
+	set_syscall(rule, SYS_openat);
+	set_syscall(rule, SYS_setuid);
+	add_field(rule, AUDIT_PPID, AUDIT_EQUAL, getpid());
+	add_field(rule, AUDIT_FILTERKEY, AUDIT_EQUAL, strlen(KEY));
+	memcpy(rule->buf, KEY, strlen(KEY));
+	rule->buflen = strlen(KEY);
+
+	return sizeof(*rule) + rule->buflen;
+}
+
+static int wait_status(pid_t pid)
+{
+	int status;
+
+	if (waitpid(pid, &status, 0) != pid)
+		return -1;
+
+	return status;
+}
+
+static int is_exited_with_zero(int status)
+{
+	return status >= 0 && WIFEXITED(status) && WEXITSTATUS(status) == 0;
+}
+
+FN_SETUP(socket)
+{
+	struct sockaddr_nl addr = { .nl_family = AF_NETLINK };
+
+	sk_audit = CHECK(socket(AF_NETLINK, SOCK_RAW, NETLINK_AUDIT));
+	CHECK(bind(sk_audit, (struct sockaddr *)&addr, sizeof(addr)));
+}
+END_SETUP()
+
+FN_TEST(invalid_status)
+{
+	TEST_ERRNO(set_status(sk_audit, AUDIT_STATUS_ENABLED, 3, 0), EINVAL);
+	// The PID of the audit daemon must be the PID of the sender.
+	TEST_ERRNO(set_status(sk_audit, AUDIT_STATUS_PID, 0, getpid() + 1),
+		   EINVAL);
+}
+END_TEST()
+
+FN_TEST(register_daemon)
+{
+	struct audit_status status;
+	int sk;
+
+	TEST_SUCC(set_status(sk_audit, AUDIT_STATUS_ENABLED | AUDIT_STATUS_PID,
+			     1, getpid()));
+	TEST_RES(get_status(&status),
+		 status.enabled == 1 && status.pid == (__u32)getpid());
+
+	// Another socket cannot replace the audit daemon.
+	sk = TEST_SUCC(socket(AF_NETLINK, SOCK_RAW, NETLINK_AUDIT));
+	TEST_ERRNO(set_status(sk, AUDIT_STATUS_PID, 0, getpid()), EEXIST);
+	TEST_SUCC(close(sk));
+}
+END_TEST()
+
+FN_TEST(invalid_rules)
+{
+	size_t len;
+
+	len = build_rule();
+	req.rule.flags = AUDIT_FILTER_ENTRY;
+	TEST_ERRNO(send_with_ack(sk_audit, len, AUDIT_ADD_RULE), EINVAL);
+
+	len = build_rule();
+	req.rule.fieldflags[0] = 0;
+	TEST_ERRNO(send_with_ack(sk_audit, len, AUDIT_ADD_RULE), EINVAL);
+
+	len = build_rule();
+	req.rule.values[1] = sizeof(req.rule_buf) * 2;
+	TEST_ERRNO(send_with_ack(sk_audit, len, AUDIT_ADD_RULE), EINVAL);
+
+	TEST_ERRNO(send_with_ack(sk_audit, sizeof(req.rule) / 2,
+				 AUDIT_ADD_RULE),
+		   EINVAL);
+}
+END_TEST()
+
+FN_TEST(add_rule)
+{
+	size_t len;
+
+	len = build_rule();
+	TEST_SUCC(send_with_ack(sk_audit, len, AUDIT_ADD_RULE));
+
+	len = build_rule();
+	TEST_ERRNO(send_with_ack(sk_audit, len, AUDIT_ADD_RULE), EEXIST);
+}
+END_TEST()
+
+// Returns the number of the listed rules with the key.
+static int list_rules(void)
+{
+	struct nlmsghdr *hdr;
+	struct audit_rule_data *rule;
+	int len, count = 0;
+
+	memset(&req, 0, sizeof(req));
+	if (send_request(sk_audit, 0, AUDIT_LIST_RULES, 0) < 0)
+		return -1;
+
+	for (;;) {
+		len = recv(sk_audit, buf, BUF_SIZE, 0);
+		if (len < 0)
+			return -1;
+
+		for (hdr = (struct nlmsghdr *)buf; NLMSG_OK(hdr, len);
+		     hdr = NLMSG_NEXT(hdr, len)) {
+			// Skip the records.
+			if (hdr->nlmsg_seq != req.hdr.nlmsg_seq)
+				break;
+			if (hdr->nlmsg_type == NLMSG_DONE)
+				return count;
+			if (hdr->nlmsg_type != AUDIT_LIST_RULES)
+				return -1;
+
+			rule = NLMSG_DATA(hdr);
+			if (rule->buflen == strlen(KEY) &&
+			    memcmp(rule->buf, KEY, strlen(KEY)) == 0)
+				count++;
+		}
+	}
+}
+
+FN_TEST(list_rules)
+{
+	TEST_RES(list_rules(), _ret == 1);
+}
+END_TEST()
+
+// Receives the available records.
+static int recv_records(void)
+{
+	struct nlmsghdr *hdr;
+	int len;
+
+	for (;;) {
+		// The records are delivered with their own messages, so each
+		// datagram contains exactly one message.
+		len = recv(sk_audit, buf, BUF_SIZE / 2, MSG_DONTWAIT);
+		if (len < 0 && errno == EAGAIN) {
+			errno = 0;
+			return 0;
+		}
+		if (len < 0)
+			return -1;
+
+		hdr = (struct nlmsghdr *)buf;
+		if (len >= NLMSG_HDRLEN)
+			check_record(hdr, len);
+	}
+}
+
+FN_TEST(syscall_records)
+{
+	pid_t pid;
+
+	pid = TEST_SUCC(fork());
+	if (pid == 0) {
+		int fd = open("/dev/null", O_RDONLY);
+
+		if (fd < 0)
+			_exit(1);
+		close(fd);
+		if (setuid(getuid()) < 0)
+			_exit(2);
+		_exit(0);
+	}
+	TEST_RES(wait_status(pid), is_exited_with_zero(_ret));
+
+	TEST_RES(recv_records(), found_open && found_path && found_setuid &&
+					 found_eoe);
+}
+END_TEST()
+
+FN_TEST(user_message)
+{
+	memset(&req, 0, sizeof(req));
+	strcpy(req.text, USER_MSG);
+	TEST_SUCC(send_with_ack(sk_audit, strlen(USER_MSG) + 1, AUDIT_USER));
+
+	TEST_RES(recv_records(), found_user);
+}
+END_TEST()
+
+FN_TEST(permission)
+{
+	pid_t pid;
+
+	pid = TEST_SUCC(fork());
+	if (pid == 0) {
+		int sk = socket(AF_NETLINK, SOCK_RAW, NETLINK_AUDIT);
+
+		if (sk < 0)
+			_exit(1);
+		if (setuid(65534) < 0)
+			_exit(2);
+		if (set_status(sk, AUDIT_STATUS_ENABLED, 0, 0) != -1 ||
+		    errno != EPERM)
+			_exit(3);
+		_exit(0);
+	}
+	TEST_RES(wait_status(pid), is_exited_with_zero(_ret));
+}
+END_TEST()
+
+FN_TEST(del_rule)
+{
+	size_t len;
+
+	len = build_rule();
+	TEST_SUCC(send_with_ack(sk_audit, len, AUDIT_DEL_RULE));
+
+	len = build_rule();
+	TEST_ERRNO(send_with_ack(sk_audit, len, AUDIT_DEL_RULE), ENOENT);
+
+	TEST_RES(list_rules(), _ret == 0);
+}
+END_TEST()
+
+FN_TEST(unregister_daemon)
+{
+	struct audit_status status;
+
+	TEST_SUCC(set_status(sk_audit, AUDIT_STATUS_ENABLED | AUDIT_STATUS_PID,
+			     0, 0));
+	TEST_RES(get_status(&status), status.enabled == 0 && status.pid == 0);
+	TEST_SUCC(close(sk_audit));
+}
+END_TEST()
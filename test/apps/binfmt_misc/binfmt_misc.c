@@ -0,0 +1,197 @@
+// SPDX-License-Identifier: MPL-2.0
+
+#define _GNU_SOURCE
+
+#include "../network/test.h"
+
+#include <fcntl.h>
+#include <stdio.h>
+#include <stdlib.h>
+#include <string.h>
+#include <sys/mount.h>
+#include <sys/stat.h>
+#include <sys/wait.h>
+#include <unistd.h>
+
+#define ROOT "/tmp/binfmt_misc"
+#define INTERP "/tmp/binfmt_interp"
+#define SCRIPT "/tmp/binfmt_script"
+#define EXT_FILE "/tmp/binfmt_file.bftest"
+#define MAGIC_FILE "/tmp/binfmt_magic"
+#define OUTPUT "/tmp/binfmt_output"
+
+static char buf[256];
+
+static int write_file(const char *path, const char *content, mode_t mode)
+{
+	int fd, ret;
+
+	fd = open(path, O_WRONLY | O_CREAT | O_TRUNC, mode);
+	if (fd < 0)
+		return -1;
+	ret = write(fd, content, strlen(content));
+	close(fd);
+
+	return ret;
+}
+
+static int read_file(const char *path)
+{
+	int fd, ret;
+
+	fd = open(path, O_RDONLY);
+	if (fd < 0)
+		return -1;
+	ret = read(fd, buf, sizeof(buf) - 1);
+	close(fd);
+
+	buf[ret < 0 ? 0 : ret] = '\0';
+	return ret;
+}
+
+/*
+ * Runs the executable and returns its exit code, or the errno if it cannot be
+ * executed.
+ */
+static int run(const char *path)
+{
+	char *const argv[] = { "argv0", "arg1", NULL };
+	char *const envp[] = { NULL };
+	int status;
+	pid_t pid;
+
+	unlink(OUTPUT);
+
+	pid = fork();
+	if (pid < 0)
+		return -1;
+	if (pid == 0) {
+		execve(path, argv, envp);
+		_exit(errno);
+	}
+	if (waitpid(pid, &status, 0) < 0 || !WIFEXITED(status))
+		return -1;
+
+	errno = 0;
+	return WEXITSTATUS(status);
+}
+
+FN_SETUP(files)
+{
+	// The interpreter prints its arguments, including `$0`.
+	CHECK(write_file(INTERP,
+			 "#!/bin/sh\n"
+			 "for arg in \"$0\" \"$@\"; do echo \"$arg\"; done"
+			 " > " OUTPUT "\n",
+			 0755));
+	CHECK(write_file(SCRIPT, "#!" INTERP "  -a  b \t\nexit 1\n", 0755));
+	CHECK(write_file(EXT_FILE, "extension\n", 0755));
+	CHECK(write_file(MAGIC_FILE, "\x7f" "BFT\n", 0755));
+}
+END_SETUP()
+
+FN_TEST(shebang)
+{
+	// The interpreter is a script as well.
+	TEST_RES(run(SCRIPT), _ret == 0);
+	TEST_RES(read_file(OUTPUT),
+		 strcmp(buf, INTERP "\n-a  b\n" SCRIPT "\narg1\n") == 0);
+}
+END_TEST()
+
+FN_SETUP(mount)
+{
+	CHECK(mkdir(ROOT, 0755));
+	CHECK(mount("none", ROOT, "binfmt_misc", 0, NULL));
+}
+END_SETUP()
+
+FN_TEST(extension)
+{
+	TEST_RES(run(EXT_FILE), _ret == ENOEXEC);
+
+	TEST_SUCC(write_file(ROOT "/register",
+			     ":bftest_ext:E::bftest::" INTERP ":", 0));
+	TEST_ERRNO(write_file(ROOT "/register",
+			      ":bftest_ext:E::bftest::" INTERP ":", 0),
+		   EEXIST);
+	TEST_RES(read_file(ROOT "/bftest_ext"),
+		 strcmp(buf, "enabled\n"
+			     "interpreter " INTERP "\n"
+			     "flags: \n"
+			     "extension .bftest\n") == 0);
+
+	TEST_RES(run(EXT_FILE), _ret == 0);
+	TEST_RES(read_file(OUTPUT),
+		 strcmp(buf, INTERP "\n" EXT_FILE "\narg1\n") == 0);
+
+	TEST_SUCC(write_file(ROOT "/bftest_ext", "0", 0));
+	TEST_RES(read_file(ROOT "/bftest_ext"),
+		 strncmp(buf, "disabled\n", 9) == 0);
+	TEST_RES(run(EXT_FILE), _ret == ENOEXEC);
+
+	TEST_SUCC(write_file(ROOT "/bftest_ext", "1", 0));
+	TEST_RES(run(EXT_FILE), _ret == 0);
+
+	TEST_SUCC(write_file(ROOT "/bftest_ext", "-1", 0));
+	TEST_ERRNO(read_file(ROOT "/bftest_ext"), ENOENT);
+	TEST_RES(run(EXT_FILE), _ret == ENOEXEC);
+}
+END_TEST()
+
+FN_TEST(magic)
+{
+	TEST_ERRNO(write_file(ROOT "/register",
+			      ":bftest_magic:M::\\x7fBFT:\\xff:" INTERP ":",
+			      0),
+		   EINVAL);
+	TEST_SUCC(write_file(ROOT "/register",
+			     ":bftest_magic:M:1:BFT:\\xff\\xdf\\xff:" INTERP
+			     ":P",
+			     0));
+	TEST_RES(read_file(ROOT "/bftest_magic"),
+		 strcmp(buf, "enabled\n"
+			     "interpreter " INTERP "\n"
+			     "flags: P\n"
+			     "offset 1\n"
+			     "magic 424654\n"
+			     "mask ffdfff\n") == 0);
+
+	// The original `argv[0]` is preserved.
+	TEST_RES(run(MAGIC_FILE), _ret == 0);
+	TEST_RES(read_file(OUTPUT),
+		 strcmp(buf, INTERP "\n" MAGIC_FILE "\nargv0\narg1\n") == 0);
+
+	TEST_SUCC(write_file(ROOT "/bftest_magic", "-1", 0));
+	TEST_ERRNO(read_file(ROOT "/bftest_magic"), ENOENT);
+}
+END_TEST()
+
+FN_TEST(status)
+{
+	TEST_RES(read_file(ROOT "/status"), strcmp(buf, "enabled\n") == 0);
+	TEST_ERRNO(write_file(ROOT "/status", "2", 0), EINVAL);
+
+	TEST_SUCC(write_file(ROOT "/register",
+			     ":bftest_ext:E::bftest::" INTERP ":", 0));
+	TEST_SUCC(write_file(ROOT "/status", "0", 0));
+	TEST_RES(read_file(ROOT "/status"), strcmp(buf, "disabled\n") == 0);
+	TEST_RES(run(EXT_FILE), _ret == ENOEXEC);
+
+	TEST_SUCC(write_file(ROOT "/status", "1", 0));
+	TEST_RES(run(EXT_FILE), _ret == 0);
+	TEST_SUCC(write_file(ROOT "/bftest_ext", "-1", 0));
+}
+END_TEST()
+
+FN_SETUP(cleanup)
+{
+	CHECK(umount(ROOT));
+	CHECK(rmdir(ROOT));
+	CHECK(unlink(INTERP));
+	CHECK(unlink(SCRIPT));
+	CHECK(unlink(EXT_FILE));
+	CHECK(unlink(MAGIC_FILE));
+	CHECK(unlink(OUTPUT));
+}
+END_SETUP()
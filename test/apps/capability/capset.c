@@ -0,0 +1,269 @@
+// SPDX-License-Identifier: MPL-2.0
+
+#define _GNU_SOURCE
+
+#include <linux/capability.h>
+#include <fcntl.h>
+#include <netinet/in.h>
+#include <sched.h>
+#include <signal.h>
+#include <stdio.h>
+#include <stdlib.h>
+#include <string.h>
+#include <sys/prctl.h>
+#include <sys/resource.h>
+#include <sys/socket.h>
+#include <sys/syscall.h>
+#include <sys/wait.h>
+#include <unistd.h>
+
+#include "../network/test.h"
+
+#define CAP_BIT(cap) (1ULL << (cap))
+#define NOBODY 65534
+
+struct caps {
+	unsigned long long effective;
+	unsigned long long permitted;
+	unsigned long long inheritable;
+};
+
+static int get_caps(struct caps *caps)
+{
+	struct __user_cap_header_struct header = {
+		.version = _LINUX_CAPABILITY_VERSION_3,
+		.pid = 0,
+	};
+	struct __user_cap_data_struct data[2];
+
+	if (syscall(SYS_capget, &header, data) < 0)
+		return -1;
+
+	caps->effective = data[0].effective |
+			  ((unsigned long long)data[1].effective << 32);
+	caps->permitted = data[0].permitted |
+			  ((unsigned long long)data[1].permitted << 32);
+	caps->inheritable = data[0].inheritable |
+			    ((unsigned long long)data[1].inheritable << 32);
+	return 0;
+}
+
+static int set_caps(const struct caps *caps)
+{
+	struct __user_cap_header_struct header = {
+		.version = _LINUX_CAPABILITY_VERSION_3,
+		.pid = 0,
+	};
+	struct __user_cap_data_struct data[2] = {
+		{
+			.effective = caps->effective,
+			.permitted = caps->permitted,
+			.inheritable = caps->inheritable,
+		},
+		{
+			.effective = caps->effective >> 32,
+			.permitted = caps->permitted >> 32,
+			.inheritable = caps->inheritable >> 32,
+		},
+	};
+
+	return syscall(SYS_capset, &header, data);
+}
+
+static struct caps orig_caps;
+
+FN_SETUP(get_orig_caps)
+{
+	CHECK(get_caps(&orig_caps));
+	orig_caps.inheritable = 0;
+	CHECK(set_caps(&orig_caps));
+}
+END_SETUP()
+
+FN_TEST(capget_version)
+{
+	struct __user_cap_header_struct header = { .version = 0, .pid = 0 };
+	struct __user_cap_data_struct data[2];
+
+	TEST_ERRNO(syscall(SYS_capget, &header, data), EINVAL);
+
+	// A null data pointer is used to probe the preferred version.
+	header.version = 0;
+	TEST_SUCC(syscall(SYS_capget, &header, NULL));
+	TEST_RES(header.version, _ret == _LINUX_CAPABILITY_VERSION_3);
+
+	header.pid = getpid();
+	TEST_ERRNO(syscall(SYS_capset, &header, NULL), EFAULT);
+}
+END_TEST()
+
+FN_TEST(privileged_ops)
+{
+	struct sockaddr_in addr = {
+		.sin_family = AF_INET,
+		.sin_port = htons(80),
+		.sin_addr = { htonl(INADDR_LOOPBACK) },
+	};
+	struct caps caps = orig_caps;
+	int sk;
+
+	// The capabilities are dropped from the effective set only.
+	caps.effective &= ~(CAP_BIT(CAP_SYS_CHROOT) |
+			    CAP_BIT(CAP_NET_BIND_SERVICE) |
+			    CAP_BIT(CAP_SYS_NICE));
+	TEST_SUCC(set_caps(&caps));
+
+	TEST_ERRNO(chroot("/"), EPERM);
+	sk = TEST_SUCC(socket(AF_INET, SOCK_STREAM, 0));
+	TEST_ERRNO(bind(sk, (struct sockaddr *)&addr, sizeof(addr)), EACCES);
+	TEST_ERRNO(setpriority(PRIO_PROCESS, 0, -5), EACCES);
+
+	// The permitted capabilities can become effective again.
+	TEST_SUCC(set_caps(&orig_caps));
+	TEST_SUCC(bind(sk, (struct sockaddr *)&addr, sizeof(addr)));
+	TEST_SUCC(setpriority(PRIO_PROCESS, 0, 0));
+	TEST_SUCC(close(sk));
+}
+END_TEST()
+
+FN_TEST(namespace_ops)
+{
+	struct caps caps = orig_caps;
+	int fd;
+
+	fd = TEST_SUCC(open("/proc/self/ns/uts", O_RDONLY));
+
+	caps.effective &= ~CAP_BIT(CAP_SYS_ADMIN);
+	TEST_SUCC(set_caps(&caps));
+
+	TEST_ERRNO(unshare(CLONE_NEWUTS), EPERM);
+	TEST_ERRNO(unshare(CLONE_NEWIPC), EPERM);
+	TEST_ERRNO(unshare(CLONE_NEWPID), EPERM);
+	TEST_ERRNO(syscall(SYS_clone, CLONE_NEWUTS | SIGCHLD, 0, 0, 0, 0),
+		   EPERM);
+	TEST_ERRNO(setns(fd, CLONE_NEWUTS), EPERM);
+
+	TEST_SUCC(set_caps(&orig_caps));
+	TEST_SUCC(setns(fd, CLONE_NEWUTS));
+	TEST_SUCC(close(fd));
+}
+END_TEST()
+
+FN_TEST(ambient)
+{
+	struct caps caps = orig_caps;
+
+	// An ambient capability must be permitted and inheritable.
+	TEST_ERRNO(prctl(PR_CAP_AMBIENT, PR_CAP_AMBIENT_RAISE,
+			 CAP_NET_BIND_SERVICE, 0, 0),
+		   EPERM);
+
+	caps.inheritable = CAP_BIT(CAP_NET_BIND_SERVICE);
+	TEST_SUCC(set_caps(&caps));
+	TEST_SUCC(prctl(PR_CAP_AMBIENT, PR_CAP_AMBIENT_RAISE,
+			CAP_NET_BIND_SERVICE, 0, 0));
+	TEST_RES(prctl(PR_CAP_AMBIENT, PR_CAP_AMBIENT_IS_SET,
+		       CAP_NET_BIND_SERVICE, 0, 0),
+		 _ret == 1);
+
+	// Dropping the inheritable capability drops the ambient one.
+	TEST_SUCC(set_caps(&orig_caps));
+	TEST_RES(prctl(PR_CAP_AMBIENT, PR_CAP_AMBIENT_IS_SET,
+		       CAP_NET_BIND_SERVICE, 0, 0),
+		 _ret == 0);
+
+	TEST_SUCC(prctl(PR_CAP_AMBIENT, PR_CAP_AMBIENT_CLEAR_ALL, 0, 0, 0));
+	TEST_ERRNO(prctl(PR_CAP_AMBIENT, PR_CAP_AMBIENT_IS_SET, 64, 0, 0),
+		   EINVAL);
+}
+END_TEST()
+
+// Runs the function in a child process and returns its exit status.
+static int run_in_child(int (*func)(void))
+{
+	int status;
+	pid_t pid;
+
+	pid = fork();
+	if (pid < 0)
+		return -1;
+	if (pid == 0)
+		_exit(func());
+
+	if (waitpid(pid, &status, 0) != pid || !WIFEXITED(status))
+		return -1;
+	return WEXITSTATUS(status);
+}
+
+static int setuid_clears_caps(void)
+{
+	struct caps caps;
+
+	if (setuid(NOBODY) < 0 || get_caps(&caps) < 0)
+		return 1;
+	return caps.effective != 0 || caps.permitted != 0;
+}
+
+static int setuid_keeps_caps(void)
+{
+	struct caps caps;
+
+	if (prctl(PR_SET_KEEPCAPS, 1, 0, 0, 0) < 0)
+		return 1;
+	if (setresuid(NOBODY, NOBODY, NOBODY) < 0 || get_caps(&caps) < 0)
+		return 1;
+	// The permitted capabilities are kept, but they are not effective.
+	if (caps.effective != 0 || caps.permitted != orig_caps.permitted)
+		return 1;
+
+	// They can become effective again.
+	caps.effective = CAP_BIT(CAP_SETUID);
+	if (set_caps(&caps) < 0 || setresuid(0, 0, 0) < 0)
+		return 1;
+	return 0;
+}
+
+FN_TEST(setuid)
+{
+	TEST_RES(run_in_child(setuid_clears_caps), _ret == 0);
+	TEST_RES(run_in_child(setuid_keeps_caps), _ret == 0);
+}
+END_TEST()
+
+FN_TEST(bounding_set)
+{
+	struct caps caps = orig_caps;
+
+	TEST_RES(prctl(PR_CAPBSET_READ, CAP_NET_RAW, 0, 0, 0), _ret == 1);
+	TEST_ERRNO(prctl(PR_CAPBSET_READ, 64, 0, 0, 0), EINVAL);
+
+	// A capability outside the bounding set cannot become inheritable.
+	TEST_SUCC(prctl(PR_CAPBSET_DROP, CAP_NET_RAW, 0, 0, 0));
+	TEST_RES(prctl(PR_CAPBSET_READ, CAP_NET_RAW, 0, 0, 0), _ret == 0);
+	caps.inheritable = CAP_BIT(CAP_NET_RAW);
+	TEST_ERRNO(set_caps(&caps), EPERM);
+}
+END_TEST()
+
+FN_TEST(capset_limits)
+{
+	struct caps caps = orig_caps;
+
+	// The effective capabilities must be permitted.
+	caps.permitted &= ~CAP_BIT(CAP_NET_ADMIN);
+	TEST_ERRNO(set_caps(&caps), EPERM);
+
+	// Dropped permitted capabilities cannot be added back.
+	caps.effective &= ~CAP_BIT(CAP_NET_ADMIN);
+	TEST_SUCC(set_caps(&caps));
+	TEST_ERRNO(set_caps(&orig_caps), EPERM);
+
+	// Without `CAP_SETPCAP`, the inheritable capabilities must be
+	// permitted.
+	caps.effective &= ~CAP_BIT(CAP_SETPCAP);
+	TEST_SUCC(set_caps(&caps));
+	caps.inheritable = CAP_BIT(CAP_NET_ADMIN);
+	TEST_ERRNO(set_caps(&caps), EPERM);
+	TEST_ERRNO(prctl(PR_CAPBSET_DROP, CAP_CHOWN, 0, 0, 0), EPERM);
+}
+END_TEST()
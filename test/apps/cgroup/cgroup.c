@@ -0,0 +1,200 @@
+// SPDX-License-Identifier: MPL-2.0
+
+#define _GNU_SOURCE
+
+#include "../network/test.h"
+
+#include <fcntl.h>
+#include <sched.h>
+#include <stdio.h>
+#include <stdlib.h>
+#include <string.h>
+#include <sys/mount.h>
+#include <sys/stat.h>
+#include <sys/wait.h>
+#include <unistd.h>
+
+#define ROOT "/tmp/cgroup"
+#define CHILD ROOT "/child"
+
+static char buf[256];
+
+static int is_only_cpu0(cpu_set_t *mask)
+{
+	return CPU_COUNT(mask) == 1 && CPU_ISSET(0, mask);
+}
+
+static int write_file(const char *path, const char *content)
+{
+	int fd, ret;
+
+	fd = open(path, O_WRONLY);
+	if (fd < 0)
+		return -1;
+	ret = write(fd, content, strlen(content));
+	close(fd);
+
+	return ret;
+}
+
+static int read_file(const char *path)
+{
+	int fd, ret;
+
+	fd = open(path, O_RDONLY);
+	if (fd < 0)
+		return -1;
+	ret = read(fd, buf, sizeof(buf) - 1);
+	close(fd);
+
+	buf[ret < 0 ? 0 : ret] = '\0';
+	return ret;
+}
+
+FN_SETUP(mount)
+{
+	CHECK(mkdir(ROOT, 0755));
+	CHECK(mount("none", ROOT, "cgroup2", 0, NULL));
+}
+END_SETUP()
+
+FN_TEST(controllers)
+{
+	TEST_RES(read_file(ROOT "/cgroup.controllers"),
+		 strcmp(buf, "cpuset cpu memory\n") == 0);
+	TEST_RES(read_file(ROOT "/cgroup.subtree_control"),
+		 strcmp(buf, "\n") == 0);
+
+	TEST_ERRNO(write_file(ROOT "/cgroup.subtree_control", "+io"), EINVAL);
+	TEST_SUCC(write_file(ROOT "/cgroup.subtree_control",
+			     "+cpu +memory +cpuset"));
+	TEST_RES(read_file(ROOT "/cgroup.subtree_control"),
+		 strcmp(buf, "cpuset cpu memory\n") == 0);
+
+	// The controller files do not exist in the root cgroup.
+	TEST_ERRNO(open(ROOT "/cpu.weight", O_RDONLY), ENOENT);
+}
+END_TEST()
+
+FN_TEST(mkdir)
+{
+	TEST_SUCC(mkdir(CHILD, 0755));
+	TEST_ERRNO(mkdir(CHILD, 0755), EEXIST);
+	TEST_ERRNO(mkdir(ROOT "/cgroup.procs", 0755), EEXIST);
+
+	TEST_RES(read_file(CHILD "/cgroup.controllers"),
+		 strcmp(buf, "cpuset cpu memory\n") == 0);
+	TEST_RES(read_file(CHILD "/cgroup.procs"), _ret == 0);
+}
+END_TEST()
+
+FN_TEST(cpu)
+{
+	TEST_RES(read_file(CHILD "/cpu.weight"), strcmp(buf, "100\n") == 0);
+	TEST_ERRNO(write_file(CHILD "/cpu.weight", "0"), ERANGE);
+	TEST_ERRNO(write_file(CHILD "/cpu.weight", "10001"), ERANGE);
+	TEST_SUCC(write_file(CHILD "/cpu.weight", "200"));
+	TEST_RES(read_file(CHILD "/cpu.weight"), strcmp(buf, "200\n") == 0);
+
+	TEST_RES(read_file(CHILD "/cpu.max"),
+		 strcmp(buf, "max 100000\n") == 0);
+	TEST_ERRNO(write_file(CHILD "/cpu.max", "10 100000"), EINVAL);
+	TEST_ERRNO(write_file(CHILD "/cpu.max", "50000 10"), EINVAL);
+	TEST_SUCC(write_file(CHILD "/cpu.max", "50000 200000"));
+	TEST_RES(read_file(CHILD "/cpu.max"),
+		 strcmp(buf, "50000 200000\n") == 0);
+	TEST_SUCC(write_file(CHILD "/cpu.max", "max"));
+	TEST_RES(read_file(CHILD "/cpu.max"),
+		 strcmp(buf, "max 200000\n") == 0);
+
+	TEST_RES(read_file(CHILD "/cpu.stat"),
+		 strncmp(buf, "usage_usec ", 11) == 0);
+}
+END_TEST()
+
+FN_TEST(memory)
+{
+	TEST_RES(read_file(CHILD "/memory.max"), strcmp(buf, "max\n") == 0);
+	TEST_ERRNO(write_file(CHILD "/memory.max", "1X"), EINVAL);
+	TEST_SUCC(write_file(CHILD "/memory.max", "1M"));
+	TEST_RES(read_file(CHILD "/memory.max"),
+		 strcmp(buf, "1048576\n") == 0);
+	TEST_SUCC(write_file(CHILD "/memory.max", "max"));
+	TEST_RES(read_file(CHILD "/memory.max"), strcmp(buf, "max\n") == 0);
+	TEST_RES(read_file(CHILD "/memory.current"), _ret > 0);
+}
+END_TEST()
+
+FN_TEST(cpuset)
+{
+	TEST_RES(read_file(ROOT "/cpuset.cpus.effective"),
+		 strncmp(buf, "0", 1) == 0);
+	TEST_ERRNO(write_file(CHILD "/cpuset.cpus", "a"), EINVAL);
+	TEST_ERRNO(write_file(CHILD "/cpuset.cpus", "100000"), EINVAL);
+	TEST_SUCC(write_file(CHILD "/cpuset.cpus", "0"));
+	TEST_RES(read_file(CHILD "/cpuset.cpus"), strcmp(buf, "0\n") == 0);
+	TEST_RES(read_file(CHILD "/cpuset.cpus.effective"),
+		 strcmp(buf, "0\n") == 0);
+}
+END_TEST()
+
+FN_TEST(procs)
+{
+	char pid_str[16], path[64];
+	cpu_set_t mask;
+	int pipefd[2];
+	pid_t pid;
+
+	TEST_SUCC(pipe(pipefd));
+
+	pid = TEST_SUCC(fork());
+	if (pid == 0) {
+		close(pipefd[1]);
+		read(pipefd[0], pid_str, 1);
+		_exit(0);
+	}
+	close(pipefd[0]);
+
+	snprintf(pid_str, sizeof(pid_str), "%d", pid);
+	snprintf(path, sizeof(path), "/proc/%d/cgroup", pid);
+
+	TEST_ERRNO(write_file(CHILD "/cgroup.procs", "0x7fffffff"), EINVAL);
+	TEST_ERRNO(write_file(CHILD "/cgroup.procs", "2147483647"), ESRCH);
+	TEST_RES(read_file(path), strcmp(buf, "0::/\n") == 0);
+	TEST_SUCC(write_file(CHILD "/cgroup.procs", pid_str));
+	TEST_RES(read_file(path), strcmp(buf, "0::/child\n") == 0);
+	TEST_RES(read_file(CHILD "/cgroup.procs"),
+		 atoi(buf) == pid && strchr(buf, '\n')[1] == '\0');
+
+	// The process is confined to the CPUs of the cgroup.
+	TEST_RES(sched_getaffinity(pid, sizeof(mask), &mask),
+		 is_only_cpu0(&mask));
+
+	// Populated cgroups cannot be removed.
+	TEST_ERRNO(rmdir(CHILD), EBUSY);
+	// Controllers cannot be disabled while they are used by the children.
+	TEST_ERRNO(write_file(ROOT "/cgroup.subtree_control", "-cpu"), EBUSY);
+
+	close(pipefd[1]);
+	TEST_RES(wait(NULL), _ret == pid);
+	TEST_RES(read_file(CHILD "/cgroup.procs"), _ret == 0);
+}
+END_TEST()
+
+FN_TEST(rmdir)
+{
+	TEST_SUCC(rmdir(CHILD));
+	TEST_ERRNO(rmdir(CHILD), ENOENT);
+	TEST_SUCC(write_file(ROOT "/cgroup.subtree_control",
+			     "-cpu -memory -cpuset"));
+	TEST_RES(read_file(ROOT "/cgroup.subtree_control"),
+		 strcmp(buf, "\n") == 0);
+}
+END_TEST()
+
+FN_SETUP(umount)
+{
+	CHECK(umount(ROOT));
+	CHECK(rmdir(ROOT));
+}
+END_SETUP()
@@ -0,0 +1,161 @@
+// SPDX-License-Identifier: MPL-2.0
+
+#define _GNU_SOURCE
+
+#include <sys/time.h>
+#include <sys/timex.h>
+#include <time.h>
+#include <unistd.h>
+
+#include "../network/test.h"
+
+static long long to_ns(const struct timespec *ts)
+{
+	return ts->tv_sec * 1000000000LL + ts->tv_nsec;
+}
+
+static long long read_ns(clockid_t clockid)
+{
+	struct timespec ts;
+
+	CHECK(clock_gettime(clockid, &ts));
+	return to_ns(&ts);
+}
+
+FN_TEST(clock_ids)
+{
+	struct timespec ts;
+
+	TEST_SUCC(clock_gettime(CLOCK_MONOTONIC_RAW, &ts));
+	TEST_SUCC(clock_gettime(CLOCK_BOOTTIME, &ts));
+	TEST_SUCC(clock_gettime(CLOCK_TAI, &ts));
+	TEST_SUCC(clock_gettime(CLOCK_REALTIME_ALARM, &ts));
+	TEST_SUCC(clock_gettime(CLOCK_BOOTTIME_ALARM, &ts));
+	TEST_ERRNO(clock_gettime(10, &ts), EINVAL);
+}
+END_TEST()
+
+FN_TEST(adjtimex_read)
+{
+	struct timex tx = { .modes = 0 };
+
+	TEST_RES(adjtimex(&tx),
+		 _ret >= TIME_OK && _ret <= TIME_ERROR &&
+			 tx.tolerance == 500 << 16 && tx.tick == 10000);
+
+	tx.modes = ADJ_TICK;
+	tx.tick = 20000;
+	TEST_ERRNO(adjtimex(&tx), EINVAL);
+
+	tx.modes = 0;
+	TEST_ERRNO(clock_adjtime(CLOCK_MONOTONIC, &tx), EOPNOTSUPP);
+	TEST_SUCC(clock_adjtime(CLOCK_REALTIME, &tx));
+}
+END_TEST()
+
+FN_TEST(set_time)
+{
+	struct timespec ts;
+	struct timeval tv;
+
+	TEST_SUCC(clock_gettime(CLOCK_REALTIME, &ts));
+	TEST_SUCC(clock_settime(CLOCK_REALTIME, &ts));
+	TEST_ERRNO(clock_settime(CLOCK_MONOTONIC, &ts), EINVAL);
+	ts.tv_nsec = 1000000000;
+	TEST_ERRNO(clock_settime(CLOCK_REALTIME, &ts), EINVAL);
+
+	TEST_SUCC(gettimeofday(&tv, NULL));
+	TEST_SUCC(settimeofday(&tv, NULL));
+	tv.tv_usec = 1000000;
+	TEST_ERRNO(settimeofday(&tv, NULL), EINVAL);
+}
+END_TEST()
+
+FN_TEST(set_offset)
+{
+	struct timex tx = { .modes = ADJ_SETOFFSET, .time = { .tv_sec = 10 } };
+	long long before, after;
+
+	before = read_ns(CLOCK_REALTIME);
+	TEST_SUCC(adjtimex(&tx));
+	after = read_ns(CLOCK_REALTIME);
+	TEST_RES(0, after - before >= 10000000000LL);
+
+	tx.time.tv_sec = -10;
+	TEST_SUCC(adjtimex(&tx));
+	TEST_RES(0, read_ns(CLOCK_REALTIME) - before < 10000000000LL);
+
+	tx.time.tv_usec = -1;
+	TEST_ERRNO(adjtimex(&tx), EINVAL);
+}
+END_TEST()
+
+FN_TEST(tai_offset)
+{
+	struct timex tx = { .modes = ADJ_TAI, .constant = 37 };
+	long long diff;
+
+	TEST_RES(adjtimex(&tx), tx.tai == 37);
+	diff = read_ns(CLOCK_TAI) - read_ns(CLOCK_REALTIME);
+	TEST_RES(0, diff > 36000000000LL && diff <= 37000000000LL);
+
+	tx.constant = 0;
+	TEST_RES(adjtimex(&tx), tx.tai == 0);
+}
+END_TEST()
+
+FN_TEST(frequency)
+{
+	struct timex tx = { .modes = ADJ_FREQUENCY, .freq = 500 << 16 };
+	long long raw, mono;
+
+	TEST_RES(adjtimex(&tx), tx.freq == 500 << 16);
+
+	// The monotonic clock runs faster than the raw clock by 500 ppm.
+	raw = read_ns(CLOCK_MONOTONIC_RAW);
+	mono = read_ns(CLOCK_MONOTONIC);
+	TEST_SUCC(usleep(200 * 1000));
+	raw = read_ns(CLOCK_MONOTONIC_RAW) - raw;
+	mono = read_ns(CLOCK_MONOTONIC) - mono;
+	TEST_RES(0, mono - raw > raw / 1000000 * 250);
+
+	tx.freq = 1000 << 16;
+	TEST_RES(adjtimex(&tx), tx.freq == 500 << 16);
+
+	tx.freq = 0;
+	TEST_RES(adjtimex(&tx), tx.freq == 0);
+}
+END_TEST()
+
+FN_TEST(single_shot)
+{
+	struct timex tx = { .modes = ADJ_OFFSET_SINGLESHOT, .offset = 1000 };
+
+	TEST_SUCC(adjtimex(&tx));
+
+	tx.modes = ADJ_OFFSET_SS_READ;
+	TEST_RES(adjtimex(&tx), tx.offset >= 0 && tx.offset <= 1000);
+
+	tx.modes = ADJ_OFFSET_SINGLESHOT;
+	tx.offset = 0;
+	TEST_RES(adjtimex(&tx), tx.offset >= 0 && tx.offset <= 1000);
+}
+END_TEST()
+
+FN_TEST(leap_second)
+{
+	struct timex tx = {
+		.modes = ADJ_STATUS | ADJ_MAXERROR,
+		.status = STA_INS,
+		.maxerror = 0,
+	};
+
+	TEST_RES(adjtimex(&tx), _ret == TIME_INS && (tx.status & STA_INS));
+
+	tx.status = 0;
+	TEST_RES(adjtimex(&tx), _ret == TIME_OK);
+
+	tx.status = STA_UNSYNC;
+	TEST_RES(adjtimex(&tx), _ret == TIME_ERROR);
+}
+END_TEST()
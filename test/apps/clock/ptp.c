@@ -0,0 +1,90 @@
+// SPDX-License-Identifier: MPL-2.0
+
+#define _GNU_SOURCE
+
+#include <fcntl.h>
+#include <linux/ptp_clock.h>
+#include <sys/ioctl.h>
+#include <time.h>
+#include <unistd.h>
+
+#include "../network/test.h"
+
+#define FD_TO_CLOCKID(fd) ((~(clockid_t)(fd) << 3) | 3)
+
+static int ptp_fd;
+
+FN_SETUP(open_ptp)
+{
+	ptp_fd = open("/dev/ptp0", O_RDWR);
+	if (ptp_fd < 0 && errno == ENOENT) {
+		// The PTP clock is only available in KVM guests.
+		fprintf(stderr, "/dev/ptp0 does not exist, skipping the tests\n");
+		exit(EXIT_SUCCESS);
+	}
+	CHECK(ptp_fd);
+}
+END_SETUP()
+
+static long long to_ns(const struct timespec *ts)
+{
+	return ts->tv_sec * 1000000000LL + ts->tv_nsec;
+}
+
+static long long ptp_to_ns(const struct ptp_clock_time *pct)
+{
+	return pct->sec * 1000000000LL + pct->nsec;
+}
+
+FN_TEST(caps)
+{
+	struct ptp_clock_caps caps;
+
+	TEST_RES(ioctl(ptp_fd, PTP_CLOCK_GETCAPS, &caps),
+		 caps.max_adj == 0 && caps.cross_timestamping == 1);
+}
+END_TEST()
+
+FN_TEST(gettime)
+{
+	struct timespec ts;
+
+	TEST_RES(clock_gettime(FD_TO_CLOCKID(ptp_fd), &ts), ts.tv_sec > 0);
+	TEST_ERRNO(clock_gettime(FD_TO_CLOCKID(0), &ts), EINVAL);
+	TEST_ERRNO(clock_gettime(FD_TO_CLOCKID(1000), &ts), EBADF);
+}
+END_TEST()
+
+FN_TEST(sys_offset)
+{
+	struct ptp_sys_offset offset = { .n_samples = 3 };
+
+	TEST_RES(ioctl(ptp_fd, PTP_SYS_OFFSET, &offset),
+		 ptp_to_ns(&offset.ts[0]) <= ptp_to_ns(&offset.ts[2]) &&
+			 ptp_to_ns(&offset.ts[2]) <= ptp_to_ns(&offset.ts[6]));
+
+	offset.n_samples = PTP_MAX_SAMPLES + 1;
+	TEST_ERRNO(ioctl(ptp_fd, PTP_SYS_OFFSET, &offset), EINVAL);
+}
+END_TEST()
+
+FN_TEST(sys_offset_precise)
+{
+	struct ptp_sys_offset_precise offset;
+	struct timespec ts;
+	long long now;
+
+	CHECK(clock_gettime(CLOCK_MONOTONIC_RAW, &ts));
+	now = to_ns(&ts);
+
+	TEST_RES(ioctl(ptp_fd, PTP_SYS_OFFSET_PRECISE, &offset),
+		 ptp_to_ns(&offset.sys_monoraw) >= now &&
+			 ptp_to_ns(&offset.device) > 0);
+}
+END_TEST()
+
+FN_SETUP(close_ptp)
+{
+	CHECK(close(ptp_fd));
+}
+END_SETUP()
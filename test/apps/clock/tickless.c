@@ -0,0 +1,63 @@
+// SPDX-License-Identifier: MPL-2.0
+
+#define _GNU_SOURCE
+
+#include <poll.h>
+#include <time.h>
+
+#include "../network/test.h"
+
+// The tick of an idle CPU is stopped for at most one second, so the timeouts
+// below must outlive at least one stopped period.
+#define TIMEOUT_MS 1500
+#define TIMEOUT_NS (TIMEOUT_MS * 1000000LL)
+// The allowed lateness, which is a few ticks.
+#define SLACK_NS (50 * 1000000LL)
+
+static long long read_ns(clockid_t clockid)
+{
+	struct timespec ts;
+
+	CHECK(clock_gettime(clockid, &ts));
+	return ts.tv_sec * 1000000000LL + ts.tv_nsec;
+}
+
+FN_TEST(long_sleep)
+{
+	struct timespec ts = { .tv_sec = TIMEOUT_MS / 1000,
+			       .tv_nsec = TIMEOUT_MS % 1000 * 1000000L };
+	long long start, elapsed;
+
+	start = read_ns(CLOCK_MONOTONIC);
+	TEST_SUCC(nanosleep(&ts, NULL));
+	elapsed = read_ns(CLOCK_MONOTONIC) - start;
+	TEST_RES(0, elapsed >= TIMEOUT_NS && elapsed < TIMEOUT_NS + SLACK_NS);
+}
+END_TEST()
+
+FN_TEST(long_timeout)
+{
+	long long start, elapsed;
+
+	// The timeouts of the wait queues are counted in jiffies.
+	start = read_ns(CLOCK_MONOTONIC);
+	TEST_RES(poll(NULL, 0, TIMEOUT_MS), _ret == 0);
+	elapsed = read_ns(CLOCK_MONOTONIC) - start;
+	TEST_RES(0, elapsed >= TIMEOUT_NS && elapsed < TIMEOUT_NS + SLACK_NS);
+}
+END_TEST()
+
+FN_TEST(jiffies_after_idle)
+{
+	struct timespec ts = { .tv_sec = TIMEOUT_MS / 1000,
+			       .tv_nsec = TIMEOUT_MS % 1000 * 1000000L };
+	long long coarse, mono;
+
+	// The coarse clock is updated on ticks. It must catch up with the
+	// missed jiffies once the CPU leaves the idle state.
+	TEST_SUCC(nanosleep(&ts, NULL));
+	coarse = read_ns(CLOCK_MONOTONIC_COARSE);
+	mono = read_ns(CLOCK_MONOTONIC);
+	TEST_RES(0, mono - coarse >= 0 && mono - coarse < SLACK_NS);
+}
+END_TEST()
@@ -0,0 +1,108 @@
+// SPDX-License-Identifier: MPL-2.0
+
+#define _GNU_SOURCE
+
+#include <sched.h>
+#include <sys/auxv.h>
+#include <sys/syscall.h>
+#include <time.h>
+#include <unistd.h>
+
+#include "../network/test.h"
+
+static long long to_ns(const struct timespec *ts)
+{
+	return ts->tv_sec * 1000000000LL + ts->tv_nsec;
+}
+
+// The C library reads the clocks with the vDSO routines.
+static long long vdso_read_ns(clockid_t clockid)
+{
+	struct timespec ts;
+
+	CHECK(clock_gettime(clockid, &ts));
+	return to_ns(&ts);
+}
+
+static long long syscall_read_ns(clockid_t clockid)
+{
+	struct timespec ts;
+
+	CHECK(syscall(SYS_clock_gettime, clockid, &ts));
+	return to_ns(&ts);
+}
+
+FN_TEST(vdso_mapped)
+{
+	TEST_RES(getauxval(AT_SYSINFO_EHDR), _ret != 0);
+}
+END_TEST()
+
+static int check_clock(clockid_t clockid)
+{
+	long long before, vdso, after;
+
+	before = syscall_read_ns(clockid);
+	vdso = vdso_read_ns(clockid);
+	after = syscall_read_ns(clockid);
+
+	// Allow a small error for the rounding of the calculations.
+	return vdso >= before - 1000 && vdso <= after + 1000;
+}
+
+FN_TEST(clocks_match_syscalls)
+{
+	TEST_RES(check_clock(CLOCK_REALTIME), _ret);
+	TEST_RES(check_clock(CLOCK_MONOTONIC), _ret);
+	TEST_RES(check_clock(CLOCK_MONOTONIC_RAW), _ret);
+	TEST_RES(check_clock(CLOCK_BOOTTIME), _ret);
+	TEST_RES(check_clock(CLOCK_TAI), _ret);
+}
+END_TEST()
+
+static int check_high_res(clockid_t clockid)
+{
+	long long start, prev, now;
+	int steps = 0;
+
+	// The clock should advance by small steps instead of only advancing when the vDSO data
+	// are updated.
+	start = prev = vdso_read_ns(clockid);
+	do {
+		now = vdso_read_ns(clockid);
+		if (now < prev)
+			return 0;
+		if (now > prev)
+			steps++;
+		prev = now;
+	} while (now - start < 200000000);
+
+	return steps > 1000;
+}
+
+FN_TEST(high_res)
+{
+	struct timespec ts;
+
+	TEST_RES(check_high_res(CLOCK_MONOTONIC), _ret);
+	TEST_RES(check_high_res(CLOCK_MONOTONIC_RAW), _ret);
+	TEST_RES(clock_getres(CLOCK_MONOTONIC, &ts),
+		 ts.tv_sec == 0 && ts.tv_nsec == 1);
+}
+END_TEST()
+
+FN_TEST(getcpu)
+{
+	cpu_set_t set;
+	unsigned int cpu, node;
+
+	CPU_ZERO(&set);
+	CPU_SET(0, &set);
+	TEST_SUCC(sched_setaffinity(0, sizeof(set), &set));
+
+	TEST_RES(sched_getcpu(), _ret == 0);
+	TEST_RES(getcpu(&cpu, &node), cpu == 0 && node == 0);
+	TEST_RES(syscall(SYS_getcpu, &cpu, &node, NULL), cpu == 0 && node == 0);
+	TEST_RES(syscall(SYS_getcpu, NULL, NULL, NULL), _ret == 0);
+}
+END_TEST()
@@ -0,0 +1,139 @@
+// SPDX-License-Identifier: MPL-2.0
+
+#define _GNU_SOURCE
+
+#include "../network/test.h"
+
+#include <fcntl.h>
+#include <linux/sched.h>
+#include <signal.h>
+#include <stdint.h>
+#include <sys/epoll.h>
+#include <sys/syscall.h>
+#include <sys/wait.h>
+#include <unistd.h>
+
+#ifndef CLONE_PIDFD
+#define CLONE_PIDFD 0x00001000
+#endif
+
+#ifndef P_PIDFD
+#define P_PIDFD 3
+#endif
+
+static int pidfd_send_signal(int pidfd, int sig, siginfo_t *info,
+			     unsigned int flags)
+{
+	return syscall(SYS_pidfd_send_signal, pidfd, sig, info, flags);
+}
+
+// Creates a child process that pauses or exits with `exit_code`, and returns its pidfd.
+static pid_t clone3_with_pidfd(int *pidfd, int exit_code)
+{
+	struct clone_args args = {
+		.flags = CLONE_PIDFD,
+		.pidfd = (uintptr_t)pidfd,
+		.exit_signal = SIGCHLD,
+	};
+	pid_t pid;
+
+	pid = syscall(SYS_clone3, &args, sizeof(args));
+	if (pid == 0) {
+		if (exit_code < 0)
+			pause();
+		_exit(exit_code);
+	}
+
+	return pid;
+}
+
+FN_TEST(clone3_pidfd)
+{
+	siginfo_t info;
+	int pidfd = -1;
+	pid_t pid;
+
+	pid = TEST_SUCC(clone3_with_pidfd(&pidfd, 3));
+	TEST_RES(fcntl(pidfd, F_GETFD), _ret == FD_CLOEXEC);
+	TEST_RES(waitid(P_PIDFD, pidfd, &info, WEXITED),
+		 info.si_pid == pid && info.si_code == CLD_EXITED &&
+			 info.si_status == 3);
+	TEST_SUCC(close(pidfd));
+}
+END_TEST()
+
+FN_TEST(clone_pidfd)
+{
+	siginfo_t info;
+	int pidfd = -1;
+	pid_t pid;
+
+	// `CLONE_PIDFD` and `CLONE_PARENT_SETTID` share the same argument.
+	TEST_ERRNO(syscall(SYS_clone, CLONE_PIDFD | CLONE_PARENT_SETTID | SIGCHLD,
+			   NULL, &pidfd, NULL, 0),
+		   EINVAL);
+
+	pid = TEST_SUCC(
+		syscall(SYS_clone, CLONE_PIDFD | SIGCHLD, NULL, &pidfd, NULL, 0));
+	if (pid == 0)
+		_exit(4);
+	TEST_RES(waitid(P_PIDFD, pidfd, &info, WEXITED),
+		 info.si_pid == pid && info.si_status == 4);
+	TEST_SUCC(close(pidfd));
+}
+END_TEST()
+
+FN_TEST(send_signal)
+{
+	siginfo_t info;
+	int pidfd = -1;
+	pid_t pid;
+
+	pid = TEST_SUCC(clone3_with_pidfd(&pidfd, -1));
+
+	TEST_ERRNO(pidfd_send_signal(pidfd, SIGKILL, NULL, 8), EINVAL);
+	TEST_ERRNO(pidfd_send_signal(STDIN_FILENO, SIGKILL, NULL, 0), EBADF);
+	TEST_SUCC(pidfd_send_signal(pidfd, 0, NULL, 0));
+
+	TEST_SUCC(pidfd_send_signal(pidfd, SIGTERM, NULL, 0));
+	TEST_RES(waitid(P_PIDFD, pidfd, &info, WEXITED | WNOWAIT),
+		 info.si_pid == pid && info.si_code == CLD_KILLED &&
+			 info.si_status == SIGTERM);
+
+	// Signals can be sent to a zombie process, but not to a reaped one.
+	TEST_SUCC(pidfd_send_signal(pidfd, SIGKILL, NULL, 0));
+	TEST_SUCC(waitid(P_PIDFD, pidfd, &info, WEXITED));
+	TEST_ERRNO(pidfd_send_signal(pidfd, SIGKILL, NULL, 0), ESRCH);
+	TEST_SUCC(close(pidfd));
+}
+END_TEST()
+
+FN_TEST(epoll_exit)
+{
+	struct epoll_event event = { .events = EPOLLIN };
+	siginfo_t info;
+	int pidfd = -1;
+	int epfd;
+
+	TEST_SUCC(clone3_with_pidfd(&pidfd, -1));
+	epfd = TEST_SUCC(epoll_create1(0));
+	event.data.fd = pidfd;
+	TEST_SUCC(epoll_ctl(epfd, EPOLL_CTL_ADD, pidfd, &event));
+
+	TEST_RES(epoll_wait(epfd, &event, 1, 0), _ret == 0);
+
+	// The pidfd becomes readable when the process exits.
+	TEST_SUCC(pidfd_send_signal(pidfd, SIGKILL, NULL, 0));
+	TEST_RES(epoll_wait(epfd, &event, 1, -1),
+		 _ret == 1 && event.events == EPOLLIN &&
+			 event.data.fd == pidfd);
+
+	// The pidfd is hung up when the process is reaped.
+	TEST_SUCC(waitid(P_PIDFD, pidfd, &info, WEXITED));
+	TEST_RES(epoll_wait(epfd, &event, 1, 0),
+		 _ret == 1 && event.events == (EPOLLIN | EPOLLHUP));
+
+	TEST_SUCC(close(epfd));
+	TEST_SUCC(close(pidfd));
+}
+END_TEST()
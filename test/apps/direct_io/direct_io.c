@@ -0,0 +1,132 @@
+// SPDX-License-Identifier: MPL-2.0
+
+#define _GNU_SOURCE
+
+#include "../network/test.h"
+#include <fcntl.h>
+#include <stdlib.h>
+#include <string.h>
+#include <sys/mman.h>
+#include <unistd.h>
+
+#define FILE_NAME "/ext2/test_direct_io.txt"
+#define BLOCK_SIZE 4096
+
+static int fd;
+static char *aligned_buf;
+static char *buffered_buf;
+
+FN_SETUP(open)
+{
+	fd = CHECK(open(FILE_NAME, O_RDWR | O_CREAT | O_TRUNC | O_DIRECT,
+			0644));
+	CHECK(posix_memalign((void **)&aligned_buf, BLOCK_SIZE,
+			     2 * BLOCK_SIZE));
+	CHECK(posix_memalign((void **)&buffered_buf, BLOCK_SIZE,
+			     2 * BLOCK_SIZE));
+}
+END_SETUP()
+
+FN_TEST(unaligned)
+{
+	TEST_ERRNO(pwrite(fd, aligned_buf, BLOCK_SIZE / 2, 0), EINVAL);
+	TEST_ERRNO(pwrite(fd, aligned_buf, BLOCK_SIZE, BLOCK_SIZE / 2),
+		   EINVAL);
+	TEST_ERRNO(pread(fd, aligned_buf, BLOCK_SIZE / 2, 0), EINVAL);
+	TEST_ERRNO(pread(fd, aligned_buf, BLOCK_SIZE, BLOCK_SIZE / 2), EINVAL);
+	TEST_ERRNO(pwrite(fd, aligned_buf + 1, BLOCK_SIZE, 0), EINVAL);
+	TEST_ERRNO(pread(fd, aligned_buf + 1, BLOCK_SIZE, 0), EINVAL);
+}
+END_TEST()
+
+FN_TEST(direct_write_then_read)
+{
+	memset(aligned_buf, 'a', 2 * BLOCK_SIZE);
+	TEST_RES(pwrite(fd, aligned_buf, 2 * BLOCK_SIZE, 0),
+		 _ret == 2 * BLOCK_SIZE);
+
+	memset(aligned_buf, 0, 2 * BLOCK_SIZE);
+	TEST_RES(pread(fd, aligned_buf, 2 * BLOCK_SIZE, 0),
+		 _ret == 2 * BLOCK_SIZE && aligned_buf[0] == 'a' &&
+			 aligned_buf[2 * BLOCK_SIZE - 1] == 'a');
+
+	TEST_RES(pread(fd, aligned_buf, BLOCK_SIZE, 2 * BLOCK_SIZE), _ret == 0);
+}
+END_TEST()
+
+FN_TEST(mixed_with_buffered_io)
+{
+	int buffered_fd;
+
+	buffered_fd = TEST_SUCC(open(FILE_NAME, O_RDWR));
+
+	// The direct write extends the file, and the buffered read should see the data.
+	memset(aligned_buf, 'b', BLOCK_SIZE);
+	TEST_RES(pwrite(fd, aligned_buf, BLOCK_SIZE, 2 * BLOCK_SIZE),
+		 _ret == BLOCK_SIZE);
+	TEST_RES(pread(buffered_fd, buffered_buf, BLOCK_SIZE, 2 * BLOCK_SIZE),
+		 _ret == BLOCK_SIZE && buffered_buf[0] == 'b' &&
+			 buffered_buf[BLOCK_SIZE - 1] == 'b');
+
+	// The buffered write is dirty in the page cache, and the direct read should see it.
+	memset(buffered_buf, 'c', BLOCK_SIZE);
+	TEST_RES(pwrite(buffered_fd, buffered_buf, BLOCK_SIZE, 0),
+		 _ret == BLOCK_SIZE);
+	TEST_RES(pread(fd, aligned_buf, BLOCK_SIZE, 0),
+		 _ret == BLOCK_SIZE && aligned_buf[0] == 'c' &&
+			 aligned_buf[BLOCK_SIZE - 1] == 'c');
+
+	// The direct read at the end of the file returns only the bytes within the file.
+	TEST_RES(pwrite(buffered_fd, "d", 1, 3 * BLOCK_SIZE), _ret == 1);
+	TEST_RES(pread(fd, aligned_buf, 2 * BLOCK_SIZE, 3 * BLOCK_SIZE),
+		 _ret == 1 && aligned_buf[0] == 'd');
+
+	TEST_SUCC(close(buffered_fd));
+}
+END_TEST()
+
+FN_TEST(toggle_with_fcntl)
+{
+	int flags;
+
+	flags = TEST_SUCC(fcntl(fd, F_GETFL));
+	TEST_SUCC(fcntl(fd, F_SETFL, flags & ~O_DIRECT));
+	TEST_RES(pwrite(fd, "e", 1, 1), _ret == 1);
+	TEST_RES(fcntl(fd, F_GETFL), (_ret & O_DIRECT) == 0);
+
+	TEST_SUCC(fcntl(fd, F_SETFL, flags | O_DIRECT));
+	TEST_ERRNO(pwrite(fd, "e", 1, 1), EINVAL);
+	TEST_RES(fcntl(fd, F_GETFL), (_ret & O_DIRECT) != 0);
+}
+END_TEST()
+
+FN_TEST(read_only_buffer)
+{
+	char *buf;
+
+	buf = mmap(NULL, BLOCK_SIZE, PROT_READ, MAP_PRIVATE | MAP_ANONYMOUS, -1,
+		   0);
+	TEST_RES(buf == MAP_FAILED ? -1 : 0, _ret == 0);
+
+	// The device cannot write to the read-only buffer.
+	TEST_ERRNO(pread(fd, buf, BLOCK_SIZE, 0), EFAULT);
+
+	// The device can read the zeros from the buffer.
+	TEST_RES(pwrite(fd, buf, BLOCK_SIZE, 0), _ret == BLOCK_SIZE);
+	memset(aligned_buf, 'f', BLOCK_SIZE);
+	TEST_RES(pread(fd, aligned_buf, BLOCK_SIZE, 0),
+		 _ret == BLOCK_SIZE && aligned_buf[0] == 0 &&
+			 aligned_buf[BLOCK_SIZE - 1] == 0);
+
+	TEST_SUCC(munmap(buf, BLOCK_SIZE));
+}
+END_TEST()
+
+FN_SETUP(cleanup)
+{
+	free(aligned_buf);
+	free(buffered_buf);
+	CHECK(close(fd));
+	CHECK(unlink(FILE_NAME));
+}
+END_SETUP()
@@ -167,3 +167,55 @@ FN_TEST(epoll_flags_oneshot)
 	TEST_SUCC(close(wfd));
 }
 END_TEST()
+
+FN_TEST(epoll_flags_exclusive)
+{
+	int fildes[2];
+	int epfd, epfd2, rfd, wfd;
+	struct epoll_event ev;
+
+	// Setup pipes
+	TEST_SUCC(pipe(fildes));
+	rfd = fildes[0];
+	wfd = fildes[1];
+
+	// Setup epoll
+	epfd = TEST_SUCC(epoll_create1(0));
+	epfd2 = TEST_SUCC(epoll_create1(0));
+
+	// Invalid flags or events with EPOLLEXCLUSIVE
+	ev.events = EPOLLIN | EPOLLEXCLUSIVE | EPOLLONESHOT;
+	ev.data.fd = rfd;
+	TEST_ERRNO(epoll_ctl(epfd, EPOLL_CTL_ADD, rfd, &ev), EINVAL);
+	ev.events = EPOLLIN | EPOLLEXCLUSIVE | EPOLLRDHUP;
+	TEST_ERRNO(epoll_ctl(epfd, EPOLL_CTL_ADD, rfd, &ev), EINVAL);
+	ev.events = EPOLLIN | EPOLLEXCLUSIVE;
+	TEST_ERRNO(epoll_ctl(epfd, EPOLL_CTL_ADD, epfd2, &ev), EINVAL);
+
+	// Add the same file to two epoll files exclusively
+	ev.events = EPOLLIN | EPOLLEXCLUSIVE;
+	ev.data.fd = rfd;
+	TEST_SUCC(epoll_ctl(epfd, EPOLL_CTL_ADD, rfd, &ev));
+	TEST_SUCC(epoll_ctl(epfd2, EPOLL_CTL_ADD, rfd, &ev));
+
+	// Exclusive entries cannot be modified
+	TEST_ERRNO(epoll_ctl(epfd, EPOLL_CTL_MOD, rfd, &ev), EINVAL);
+	ev.events = EPOLLIN;
+	TEST_ERRNO(epoll_ctl(epfd, EPOLL_CTL_MOD, rfd, &ev), EINVAL);
+
+	// No one is waiting, so both epoll files should be notified
+	TEST_SUCC(write(wfd, "", 1));
+	TEST_RES(epoll_wait(epfd, &ev, 1, 0), _ret == 1 && ev.data.fd == rfd);
+	TEST_RES(epoll_wait(epfd2, &ev, 1, 0), _ret == 1 && ev.data.fd == rfd);
+
+	// Exclusive entries can still be deleted
+	TEST_SUCC(epoll_ctl(epfd, EPOLL_CTL_DEL, rfd, NULL));
+	TEST_RES(epoll_wait(epfd, &ev, 1, 0), _ret == 0);
+
+	// Clean up
+	TEST_SUCC(close(epfd));
+	TEST_SUCC(close(epfd2));
+	TEST_SUCC(close(rfd));
+	TEST_SUCC(close(wfd));
+}
+END_TEST()
@@ -0,0 +1,63 @@
+// SPDX-License-Identifier: MPL-2.0
+
+#define _GNU_SOURCE
+
+#include <fcntl.h>
+#include <stdint.h>
+#include <sys/eventfd.h>
+#include <unistd.h>
+
+#include "../network/test.h"
+
+FN_TEST(flags)
+{
+	int fd;
+
+	fd = TEST_SUCC(eventfd(0, EFD_CLOEXEC | EFD_NONBLOCK));
+	TEST_RES(fcntl(fd, F_GETFD), _ret == FD_CLOEXEC);
+	TEST_RES(fcntl(fd, F_GETFL), (_ret & O_NONBLOCK) != 0);
+	TEST_SUCC(close(fd));
+
+	TEST_ERRNO(eventfd(0, 0x1), EINVAL);
+}
+END_TEST()
+
+FN_TEST(counter)
+{
+	uint64_t val;
+	int fd;
+
+	fd = TEST_SUCC(eventfd(0, EFD_NONBLOCK));
+
+	TEST_ERRNO(read(fd, &val, sizeof(val)), EAGAIN);
+
+	val = UINT64_MAX;
+	TEST_ERRNO(write(fd, &val, sizeof(val)), EINVAL);
+	TEST_ERRNO(write(fd, &val, sizeof(val) - 1), EINVAL);
+
+	val = UINT64_MAX - 1;
+	TEST_SUCC(write(fd, &val, sizeof(val)));
+	val = 1;
+	TEST_ERRNO(write(fd, &val, sizeof(val)), EAGAIN);
+
+	TEST_RES(read(fd, &val, sizeof(val)), val == UINT64_MAX - 1);
+	TEST_ERRNO(read(fd, &val, sizeof(val)), EAGAIN);
+
+	TEST_SUCC(close(fd));
+}
+END_TEST()
+
+FN_TEST(semaphore)
+{
+	uint64_t val;
+	int fd;
+
+	fd = TEST_SUCC(eventfd(2, EFD_SEMAPHORE | EFD_NONBLOCK));
+
+	TEST_RES(read(fd, &val, sizeof(val)), val == 1);
+	TEST_RES(read(fd, &val, sizeof(val)), val == 1);
+	TEST_ERRNO(read(fd, &val, sizeof(val)), EAGAIN);
+
+	TEST_SUCC(close(fd));
+}
+END_TEST()
@@ -0,0 +1,74 @@
+// SPDX-License-Identifier: MPL-2.0
+
+#define _GNU_SOURCE
+
+#include <pthread.h>
+#include <sys/wait.h>
+#include <unistd.h>
+
+#include "../network/test.h"
+
+#define NR_THREADS_PATH "/test/execve/nr_threads"
+
+static void *spin(void *arg)
+{
+	for (;;)
+		sched_yield();
+	return NULL;
+}
+
+static void *execve_nr_threads(void *arg)
+{
+	char *argv[] = { NR_THREADS_PATH, NULL };
+	char *envp[] = { NULL };
+
+	execve(NR_THREADS_PATH, argv, envp);
+	_exit(254);
+}
+
+static int wait_exit_code(pid_t pid)
+{
+	int status;
+
+	if (waitpid(pid, &status, 0) != pid || !WIFEXITED(status))
+		return -1;
+	return WEXITSTATUS(status);
+}
+
+FN_TEST(execve_kills_other_threads)
+{
+	pthread_t thread;
+	pid_t pid;
+
+	pid = TEST_SUCC(fork());
+	if (pid == 0) {
+		CHECK_WITH(pthread_create(&thread, NULL, spin, NULL),
+			   _ret == 0);
+		CHECK_WITH(pthread_create(&thread, NULL, spin, NULL),
+			   _ret == 0);
+		execve_nr_threads(NULL);
+	}
+
+	// The new program runs in the only thread of the process.
+	TEST_RES(wait_exit_code(pid), _ret == 1);
+}
+END_TEST()
+
+FN_TEST(execve_in_other_thread)
+{
+	pthread_t thread;
+	pid_t pid;
+
+	pid = TEST_SUCC(fork());
+	if (pid == 0) {
+		CHECK_WITH(pthread_create(&thread, NULL, execve_nr_threads,
+					  NULL),
+			   _ret == 0);
+		spin(NULL);
+	}
+
+	// The main thread is killed, and the thread that calls `execve`
+	// takes over the main thread to run the new program.
+	TEST_RES(wait_exit_code(pid), _ret == 1);
+}
+END_TEST()
@@ -0,0 +1,28 @@
+// SPDX-License-Identifier: MPL-2.0
+
+#include <stdio.h>
+#include <sys/syscall.h>
+#include <unistd.h>
+
+// Exits with the number of threads in the current process.
+int main(void)
+{
+	FILE *file;
+	char line[128];
+	int nr_threads = -1;
+
+	// The program must run in the main thread.
+	if (syscall(SYS_gettid) != getpid())
+		return 253;
+
+	file = fopen("/proc/self/status", "r");
+	if (file == NULL)
+		return 255;
+	while (fgets(line, sizeof(line), file) != NULL) {
+		if (sscanf(line, "Threads: %d", &nr_threads) == 1)
+			break;
+	}
+	fclose(file);
+
+	return nr_threads;
+}
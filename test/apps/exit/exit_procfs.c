@@ -10,29 +10,11 @@
 #define EXIT_PARENT_FIRST ((void *)1)
 #define EXIT_CHILD_FIRST ((void *)2)
 
-// TODO: Use `system` directly after we implement `vfork`.
-static int mini_system(const char *cmd)
-{
-	int pid;
-	int stat;
-
-	pid = CHECK(fork());
-
-	if (pid == 0) {
-		CHECK(execlp("sh", "sh", "-c", cmd, NULL));
-		exit(-1);
-	}
-
-	CHECK_WITH(waitpid(pid, &stat, 0), _ret == pid && WIFEXITED(stat));
-
-	return WEXITSTATUS(stat);
-}
-
 static void *thread_slave(void *arg)
 {
 	if (arg == EXIT_CHILD_FIRST) {
 		CHECK_WITH(
-			mini_system(
+			system(
 				"cat /proc/$PPID/status | grep '^Threads:\t2$'"),
 			_ret == 0);
 
@@ -42,7 +24,7 @@ static void *thread_slave(void *arg)
 		// have two threads.
 		usleep(200 * 1000);
 		CHECK_WITH(
-			mini_system(
+			system(
 				"cat /proc/$PPID/status | grep '^Threads:\t2$'"),
 			_ret == 0);
 
@@ -60,7 +42,7 @@ static void thread_master(void *arg)
 
 	if (arg == EXIT_PARENT_FIRST) {
 		CHECK_WITH(
-			mini_system(
+			system(
 				"cat /proc/$PPID/status | grep '^Threads:\t2$'"),
 			_ret == 0);
 
@@ -70,7 +52,7 @@ static void thread_master(void *arg)
 		// freed, so we only have one thread.
 		usleep(200 * 1000);
 		CHECK_WITH(
-			mini_system(
+			system(
 				"cat /proc/$PPID/status | grep '^Threads:\t1$'"),
 			_ret == 0);
 
@@ -0,0 +1,217 @@
+// SPDX-License-Identifier: MPL-2.0
+
+#define _GNU_SOURCE
+
+#include "../network/test.h"
+
+#include <fcntl.h>
+#include <poll.h>
+#include <signal.h>
+#include <sys/prctl.h>
+#include <sys/syscall.h>
+#include <sys/wait.h>
+#include <unistd.h>
+
+#ifndef P_PIDFD
+#define P_PIDFD 3
+#endif
+
+#ifndef PIDFD_NONBLOCK
+#define PIDFD_NONBLOCK O_NONBLOCK
+#endif
+
+static pid_t fork_and_pause(void)
+{
+	pid_t pid;
+
+	pid = fork();
+	if (pid == 0) {
+		pause();
+		_exit(EXIT_FAILURE);
+	}
+
+	return pid;
+}
+
+static int pidfd_open(pid_t pid, unsigned int flags)
+{
+	return syscall(SYS_pidfd_open, pid, flags);
+}
+
+FN_TEST(invalid_options)
+{
+	siginfo_t info;
+
+	TEST_ERRNO(waitid(P_ALL, 0, &info, 0), EINVAL);
+	TEST_ERRNO(waitid(P_ALL, 0, &info, WNOHANG), EINVAL);
+	TEST_ERRNO(waitid(4, 0, &info, WEXITED), EINVAL);
+	TEST_ERRNO(syscall(SYS_wait4, -1, NULL, WNOWAIT, NULL), EINVAL);
+	TEST_ERRNO(syscall(SYS_wait4, -1, NULL, WEXITED, NULL), EINVAL);
+	TEST_ERRNO(waitid(P_ALL, 0, &info, WEXITED), ECHILD);
+}
+END_TEST()
+
+FN_TEST(exited)
+{
+	siginfo_t info;
+	pid_t pid;
+
+	pid = TEST_SUCC(fork());
+	if (pid == 0)
+		_exit(7);
+
+	// `WNOWAIT` leaves the child in the waitable state.
+	TEST_RES(waitid(P_PID, pid, &info, WEXITED | WNOWAIT),
+		 info.si_signo == SIGCHLD && info.si_pid == pid &&
+			 info.si_uid == getuid() && info.si_code == CLD_EXITED &&
+			 info.si_status == 7);
+	TEST_RES(waitid(P_ALL, 0, &info, WEXITED | WNOWAIT),
+		 info.si_pid == pid && info.si_status == 7);
+	TEST_RES(waitid(P_PID, pid, &info, WEXITED),
+		 info.si_pid == pid && info.si_code == CLD_EXITED &&
+			 info.si_status == 7);
+	TEST_ERRNO(waitid(P_PID, pid, &info, WEXITED), ECHILD);
+}
+END_TEST()
+
+FN_TEST(killed)
+{
+	siginfo_t info;
+	pid_t pid;
+
+	pid = TEST_SUCC(fork_and_pause());
+
+	// No child has exited, so `si_pid` is cleared.
+	info.si_pid = -1;
+	TEST_RES(waitid(P_PID, pid, &info, WEXITED | WNOHANG),
+		 info.si_pid == 0);
+
+	TEST_SUCC(kill(pid, SIGKILL));
+	TEST_RES(waitid(P_PID, pid, &info, WEXITED),
+		 info.si_pid == pid && info.si_code == CLD_KILLED &&
+			 info.si_status == SIGKILL);
+}
+END_TEST()
+
+FN_TEST(process_group)
+{
+	siginfo_t info;
+	pid_t pid;
+
+	pid = TEST_SUCC(fork_and_pause());
+	TEST_SUCC(setpgid(pid, pid));
+
+	TEST_SUCC(kill(pid, SIGKILL));
+	TEST_ERRNO(waitid(P_PGID, getpgid(0), &info, WEXITED), ECHILD);
+	TEST_RES(waitid(P_PGID, pid, &info, WEXITED), info.si_pid == pid);
+
+	// Zero means the process group of the caller.
+	pid = TEST_SUCC(fork_and_pause());
+	TEST_SUCC(kill(pid, SIGKILL));
+	TEST_RES(waitid(P_PGID, 0, &info, WEXITED), info.si_pid == pid);
+}
+END_TEST()
+
+FN_TEST(pidfd)
+{
+	struct pollfd pfd = { .events = POLLIN };
+	siginfo_t info;
+	pid_t pid;
+	int pidfd;
+
+	TEST_ERRNO(pidfd_open(0x3fffffff, 0), ESRCH);
+	TEST_ERRNO(pidfd_open(-1, 0), EINVAL);
+	TEST_ERRNO(pidfd_open(getpid(), 1), EINVAL);
+
+	pid = TEST_SUCC(fork_and_pause());
+	pidfd = TEST_SUCC(pidfd_open(pid, 0));
+	TEST_RES(fcntl(pidfd, F_GETFD), _ret == FD_CLOEXEC);
+
+	pfd.fd = pidfd;
+	TEST_RES(poll(&pfd, 1, 0), _ret == 0);
+	TEST_ERRNO(waitid(P_PIDFD, 0, &info, WEXITED), EBADF);
+
+	// The pidfd becomes readable when the process exits.
+	TEST_SUCC(kill(pid, SIGKILL));
+	TEST_RES(poll(&pfd, 1, -1), _ret == 1 && pfd.revents == POLLIN);
+	TEST_RES(waitid(P_PIDFD, pidfd, &info, WEXITED),
+		 info.si_pid == pid && info.si_code == CLD_KILLED);
+	TEST_ERRNO(waitid(P_PIDFD, pidfd, &info, WEXITED), ECHILD);
+	TEST_SUCC(close(pidfd));
+
+	// Waiting for a non-blocking pidfd fails with `EAGAIN`.
+	pid = TEST_SUCC(fork_and_pause());
+	pidfd = TEST_SUCC(pidfd_open(pid, PIDFD_NONBLOCK));
+	TEST_ERRNO(waitid(P_PIDFD, pidfd, &info, WEXITED), EAGAIN);
+	info.si_pid = -1;
+	TEST_RES(waitid(P_PIDFD, pidfd, &info, WEXITED | WNOHANG),
+		 info.si_pid == 0);
+	TEST_SUCC(kill(pid, SIGKILL));
+	TEST_RES(poll(&pfd, 1, -1), _ret == 1);
+	TEST_RES(waitid(P_PIDFD, pidfd, &info, WEXITED), info.si_pid == pid);
+	TEST_SUCC(close(pidfd));
+}
+END_TEST()
+
+FN_TEST(child_subreaper)
+{
+	int is_subreaper;
+	pid_t self, child, grandchild;
+	int status;
+	int fds[2];
+
+	TEST_RES(prctl(PR_GET_CHILD_SUBREAPER, &is_subreaper),
+		 is_subreaper == 0);
+	TEST_SUCC(prctl(PR_SET_CHILD_SUBREAPER, 1));
+	TEST_RES(prctl(PR_GET_CHILD_SUBREAPER, &is_subreaper),
+		 is_subreaper == 1);
+
+	// A running grandchild is adopted by the subreaper.
+	self = getpid();
+	TEST_SUCC(pipe(fds));
+	child = TEST_SUCC(fork());
+	if (child == 0) {
+		grandchild = fork();
+		if (grandchild == 0) {
+			while (getppid() != self)
+				usleep(1000);
+			_exit(5);
+		}
+		write(fds[1], &grandchild, sizeof(grandchild));
+		_exit(EXIT_SUCCESS);
+	}
+	TEST_RES(read(fds[0], &grandchild, sizeof(grandchild)),
+		 _ret == sizeof(grandchild));
+	TEST_RES(waitpid(child, &status, 0),
+		 _ret == child && WIFEXITED(status) &&
+			 WEXITSTATUS(status) == EXIT_SUCCESS);
+	TEST_RES(waitpid(grandchild, &status, 0),
+		 _ret == grandchild && WIFEXITED(status) &&
+			 WEXITSTATUS(status) == 5);
+
+	// A zombie grandchild is adopted by the subreaper.
+	child = TEST_SUCC(fork());
+	if (child == 0) {
+		siginfo_t info;
+
+		grandchild = fork();
+		if (grandchild == 0)
+			_exit(6);
+		waitid(P_PID, grandchild, &info, WEXITED | WNOWAIT);
+		write(fds[1], &grandchild, sizeof(grandchild));
+		_exit(EXIT_SUCCESS);
+	}
+	TEST_RES(read(fds[0], &grandchild, sizeof(grandchild)),
+		 _ret == sizeof(grandchild));
+	TEST_RES(waitpid(child, &status, 0), _ret == child);
+	TEST_RES(waitpid(grandchild, &status, 0),
+		 _ret == grandchild && WIFEXITED(status) &&
+			 WEXITSTATUS(status) == 6);
+
+	TEST_SUCC(close(fds[0]));
+	TEST_SUCC(close(fds[1]));
+	TEST_SUCC(prctl(PR_SET_CHILD_SUBREAPER, 0));
+	TEST_RES(prctl(PR_GET_CHILD_SUBREAPER, &is_subreaper),
+		 is_subreaper == 0);
+}
+END_TEST()
@@ -0,0 +1,76 @@
+// SPDX-License-Identifier: MPL-2.0
+
+#define _GNU_SOURCE
+
+#include "../network/test.h"
+
+#include <spawn.h>
+#include <stdlib.h>
+#include <sys/wait.h>
+#include <unistd.h>
+
+extern char **environ;
+
+static volatile int shared_value;
+
+FN_TEST(shared_memory)
+{
+	volatile int stack_value = 0;
+	int status;
+	pid_t pid;
+
+	// The child of `vfork` runs in the address space of the parent, so the writes of the
+	// child are visible to the parent.
+	shared_value = 0;
+
+	pid = vfork();
+	if (pid == 0) {
+		shared_value = 1;
+		stack_value = 2;
+		_exit(EXIT_SUCCESS);
+	}
+
+	TEST_RES(waitpid(pid, &status, 0),
+		 _ret == pid && WIFEXITED(status) &&
+			 WEXITSTATUS(status) == EXIT_SUCCESS);
+	TEST_RES(shared_value, _ret == 1);
+	TEST_RES(stack_value, _ret == 2);
+}
+END_TEST()
+
+FN_TEST(execve)
+{
+	char *argv[] = { "/bin/sh", "-c", "exit 3", NULL };
+	int status;
+	pid_t pid;
+
+	// The address space of the parent must survive the `execve` of the child.
+	shared_value = 4;
+
+	pid = vfork();
+	if (pid == 0) {
+		execv(argv[0], argv);
+		_exit(EXIT_FAILURE);
+	}
+
+	TEST_RES(waitpid(pid, &status, 0),
+		 _ret == pid && WIFEXITED(status) && WEXITSTATUS(status) == 3);
+	TEST_RES(shared_value, _ret == 4);
+}
+END_TEST()
+
+FN_TEST(posix_spawn)
+{
+	char *argv[] = { "/bin/sh", "-c", "exit 5", NULL };
+	int status;
+	pid_t pid;
+
+	// `posix_spawn` is implemented with `CLONE_VM | CLONE_VFORK` by the C libraries.
+	TEST_RES(posix_spawn(&pid, argv[0], NULL, NULL, argv, environ),
+		 _ret == 0);
+	TEST_RES(waitpid(pid, &status, 0),
+		 _ret == pid && WIFEXITED(status) && WEXITSTATUS(status) == 5);
+
+	TEST_RES(system("exit 6"), WIFEXITED(_ret) && WEXITSTATUS(_ret) == 6);
+}
+END_TEST()
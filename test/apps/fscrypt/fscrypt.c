@@ -0,0 +1,251 @@
+// SPDX-License-Identifier: MPL-2.0
+
+#define _GNU_SOURCE
+
+#include "../network/test.h"
+
+#include <dirent.h>
+#include <fcntl.h>
+#include <linux/fscrypt.h>
+#include <linux/keyctl.h>
+#include <string.h>
+#include <sys/ioctl.h>
+#include <sys/stat.h>
+#include <sys/syscall.h>
+#include <unistd.h>
+
+#define TEST_DIR "/ext2/test_fscrypt"
+#define PLAIN_DIR TEST_DIR "/plain"
+#define ENC_DIR TEST_DIR "/enc"
+#define NOKEY_DIR TEST_DIR "/nokey"
+
+#define KEY_DESC "fscrypt:0123456789abcdef"
+
+static const __u8 key_descriptor[FSCRYPT_KEY_DESCRIPTOR_SIZE] = {
+	0x01, 0x23, 0x45, 0x67, 0x89, 0xab, 0xcd, 0xef,
+};
+
+static const __u8 nokey_descriptor[FSCRYPT_KEY_DESCRIPTOR_SIZE] = {
+	0xfe, 0xdc, 0xba, 0x98, 0x76, 0x54, 0x32, 0x10,
+};
+
+static struct fscrypt_policy_v1 policy;
+static struct fscrypt_policy_v1 policy_buf;
+
+static int add_master_key(void)
+{
+	struct fscrypt_key key;
+	int i;
+
+	// Like Linux, the two halves of an AES-256-XTS key must differ.
+	memset(&key, 0, sizeof(key));
+	key.mode = FSCRYPT_MODE_AES_256_XTS;
+	for (i = 0; i < FSCRYPT_MAX_KEY_SIZE; i++)
+		key.raw[i] = i;
+	key.size = FSCRYPT_MAX_KEY_SIZE;
+
+	return syscall(SYS_add_key, "logon", KEY_DESC, &key, sizeof(key),
+		       KEY_SPEC_SESSION_KEYRING);
+}
+
+static void init_policy(struct fscrypt_policy_v1 *p, const __u8 *descriptor)
+{
+	memset(p, 0, sizeof(*p));
+	p->version = FSCRYPT_POLICY_V1;
+	p->contents_encryption_mode = FSCRYPT_MODE_AES_256_XTS;
+	p->filenames_encryption_mode = FSCRYPT_MODE_AES_256_CTS;
+	p->flags = FSCRYPT_POLICY_FLAGS_PAD_32;
+	memcpy(p->master_key_descriptor, descriptor,
+	       FSCRYPT_KEY_DESCRIPTOR_SIZE);
+}
+
+static int set_policy(const char *path, struct fscrypt_policy_v1 *p)
+{
+	int fd, ret;
+
+	fd = open(path, O_RDONLY);
+	if (fd < 0)
+		return -1;
+	ret = ioctl(fd, FS_IOC_SET_ENCRYPTION_POLICY, p);
+	close(fd);
+
+	return ret;
+}
+
+static int get_policy(const char *path, struct fscrypt_policy_v1 *p)
+{
+	int fd, ret;
+
+	fd = open(path, O_RDONLY);
+	if (fd < 0)
+		return -1;
+	ret = ioctl(fd, FS_IOC_GET_ENCRYPTION_POLICY, p);
+	close(fd);
+
+	return ret;
+}
+
+static int is_same_policy(const struct fscrypt_policy_v1 *p)
+{
+	return memcmp(p, &policy, sizeof(policy)) == 0;
+}
+
+static int dir_contains(const char *path, const char *name)
+{
+	DIR *dir;
+	struct dirent *entry;
+	int found = 0;
+
+	dir = opendir(path);
+	if (dir == NULL)
+		return -1;
+	while ((entry = readdir(dir)) != NULL) {
+		if (strcmp(entry->d_name, name) == 0)
+			found = 1;
+	}
+	closedir(dir);
+
+	errno = 0;
+	return found;
+}
+
+FN_SETUP(init)
+{
+	int fd;
+
+	CHECK(mkdir(TEST_DIR, 0755));
+	CHECK(mkdir(PLAIN_DIR, 0755));
+	CHECK(mkdir(ENC_DIR, 0755));
+	CHECK(mkdir(NOKEY_DIR, 0755));
+	fd = CHECK(creat(PLAIN_DIR "/file", 0644));
+	CHECK(close(fd));
+
+	// The master key is added to a new session keyring, so it is only
+	// visible to this process.
+	CHECK(syscall(SYS_keyctl, KEYCTL_JOIN_SESSION_KEYRING, 0, 0, 0, 0));
+	CHECK(add_master_key());
+
+	init_policy(&policy, key_descriptor);
+}
+END_SETUP()
+
+FN_TEST(invalid_policy)
+{
+	init_policy(&policy_buf, key_descriptor);
+	policy_buf.version = 1;
+	TEST_ERRNO(set_policy(ENC_DIR, &policy_buf), EINVAL);
+
+	init_policy(&policy_buf, key_descriptor);
+	policy_buf.contents_encryption_mode = FSCRYPT_MODE_AES_256_CTS;
+	TEST_ERRNO(set_policy(ENC_DIR, &policy_buf), EINVAL);
+
+	init_policy(&policy_buf, key_descriptor);
+	policy_buf.flags = 0x80;
+	TEST_ERRNO(set_policy(ENC_DIR, &policy_buf), EINVAL);
+
+	TEST_ERRNO(get_policy(ENC_DIR, &policy_buf), ENODATA);
+}
+END_TEST()
+
+FN_TEST(invalid_target)
+{
+	TEST_ERRNO(set_policy(PLAIN_DIR "/file", &policy), ENOTDIR);
+	TEST_ERRNO(set_policy(PLAIN_DIR, &policy), ENOTEMPTY);
+	TEST_ERRNO(get_policy(PLAIN_DIR, &policy_buf), ENODATA);
+}
+END_TEST()
+
+FN_TEST(set_and_get_policy)
+{
+	TEST_SUCC(set_policy(ENC_DIR, &policy));
+
+	memset(&policy_buf, 0, sizeof(policy_buf));
+	TEST_RES(get_policy(ENC_DIR, &policy_buf), is_same_policy(&policy_buf));
+
+	// Setting the same policy again is a no-op.
+	TEST_SUCC(set_policy(ENC_DIR, &policy));
+
+	init_policy(&policy_buf, nokey_descriptor);
+	TEST_ERRNO(set_policy(ENC_DIR, &policy_buf), EEXIST);
+}
+END_TEST()
+
+FN_TEST(encrypted_file)
+{
+	char buf[16];
+	int fd;
+
+	fd = TEST_SUCC(open(ENC_DIR "/file", O_RDWR | O_CREAT, 0644));
+	TEST_RES(write(fd, "hello fscrypt", 13), _ret == 13);
+	TEST_SUCC(close(fd));
+
+	memset(&policy_buf, 0, sizeof(policy_buf));
+	TEST_RES(get_policy(ENC_DIR "/file", &policy_buf),
+		 is_same_policy(&policy_buf));
+
+	fd = TEST_SUCC(open(ENC_DIR "/file", O_RDONLY));
+	TEST_RES(read(fd, buf, sizeof(buf)),
+		 _ret == 13 && memcmp(buf, "hello fscrypt", 13) == 0);
+	TEST_SUCC(close(fd));
+
+	TEST_RES(dir_contains(ENC_DIR, "file"), _ret == 1);
+}
+END_TEST()
+
+FN_TEST(encrypted_dir_and_symlink)
+{
+	char buf[64];
+
+	TEST_SUCC(mkdir(ENC_DIR "/subdir", 0755));
+	memset(&policy_buf, 0, sizeof(policy_buf));
+	TEST_RES(get_policy(ENC_DIR "/subdir", &policy_buf),
+		 is_same_policy(&policy_buf));
+	TEST_RES(dir_contains(ENC_DIR, "subdir"), _ret == 1);
+
+	TEST_SUCC(symlink("subdir", ENC_DIR "/link"));
+	TEST_RES(readlink(ENC_DIR "/link", buf, sizeof(buf)),
+		 _ret == 6 && memcmp(buf, "subdir", 6) == 0);
+
+	TEST_SUCC(rename(ENC_DIR "/link", ENC_DIR "/link2"));
+	TEST_RES(dir_contains(ENC_DIR, "link2"), _ret == 1);
+	TEST_RES(dir_contains(ENC_DIR, "link"), _ret == 0);
+
+	TEST_SUCC(unlink(ENC_DIR "/link2"));
+	TEST_SUCC(rmdir(ENC_DIR "/subdir"));
+}
+END_TEST()
+
+FN_TEST(different_policy)
+{
+	// The files in an encrypted directory must have the same policy.
+	TEST_ERRNO(link(PLAIN_DIR "/file", ENC_DIR "/link"), EXDEV);
+	TEST_ERRNO(rename(PLAIN_DIR "/file", ENC_DIR "/file2"), EXDEV);
+
+	// The files can be moved out of an encrypted directory.
+	TEST_SUCC(link(ENC_DIR "/file", PLAIN_DIR "/file2"));
+	TEST_SUCC(unlink(PLAIN_DIR "/file2"));
+}
+END_TEST()
+
+FN_TEST(no_key)
+{
+	init_policy(&policy_buf, nokey_descriptor);
+	TEST_SUCC(set_policy(NOKEY_DIR, &policy_buf));
+
+	TEST_ERRNO(creat(NOKEY_DIR "/file", 0644), ENOKEY);
+	TEST_ERRNO(mkdir(NOKEY_DIR "/subdir", 0755), ENOKEY);
+	TEST_ERRNO(symlink("target", NOKEY_DIR "/link"), ENOKEY);
+	TEST_RES(dir_contains(NOKEY_DIR, "."), _ret == 1);
+}
+END_TEST()
+
+FN_SETUP(cleanup)
+{
+	CHECK(unlink(ENC_DIR "/file"));
+	CHECK(unlink(PLAIN_DIR "/file"));
+	CHECK(rmdir(ENC_DIR));
+	CHECK(rmdir(PLAIN_DIR));
+	CHECK(rmdir(NOKEY_DIR));
+	CHECK(rmdir(TEST_DIR));
+}
+END_SETUP()
@@ -0,0 +1,369 @@
+// SPDX-License-Identifier: MPL-2.0
+
+// A 32-bit (i386) program that makes system calls with `int 0x80`.
+//
+// The program does not use the C library, so the system calls are tested
+// without the thread-local storage and the signal frames that the C library
+// requires. See `ia32_libc.c` for the program that uses the C library.
+
+#define __NR_exit 1
+#define __NR_fork 2
+#define __NR_read 3
+#define __NR_write 4
+#define __NR_open 5
+#define __NR_close 6
+#define __NR_waitpid 7
+#define __NR_unlink 10
+#define __NR_time 13
+#define __NR_lseek 19
+#define __NR_getpid 20
+#define __NR_pipe 42
+#define __NR_brk 45
+#define __NR_ioctl 54
+#define __NR_gettimeofday 78
+#define __NR_munmap 91
+#define __NR_uname 122
+#define __NR__llseek 140
+#define __NR_readv 145
+#define __NR_writev 146
+#define __NR_nanosleep 162
+#define __NR_mmap2 192
+#define __NR_stat64 195
+#define __NR_lstat64 196
+#define __NR_fstat64 197
+#define __NR_gettid 224
+#define __NR_exit_group 252
+#define __NR_clock_gettime 265
+#define __NR_clock_gettime64 403
+
+#define O_RDWR 02
+#define O_CREAT 0100
+#define O_TRUNC 01000
+#define SEEK_SET 0
+#define SEEK_CUR 1
+#define PROT_READ 1
+#define PROT_WRITE 2
+#define MAP_PRIVATE 2
+#define MAP_ANONYMOUS 0x20
+#define CLOCK_MONOTONIC 1
+#define FIONBIO 0x5421
+#define TUNSETIFF 0x400454ca
+#define S_IFMT 0170000
+#define S_IFREG 0100000
+
+#define EAGAIN 11
+#define EINVAL 22
+#define ENOTTY 25
+
+#define PAGE_SIZE 4096
+#define TEST_FILE "/tmp/ia32_test"
+
+struct stat64 {
+	unsigned long long st_dev;
+	unsigned char __pad0[4];
+	unsigned int __st_ino;
+	unsigned int st_mode;
+	unsigned int st_nlink;
+	unsigned int st_uid;
+	unsigned int st_gid;
+	unsigned long long st_rdev;
+	unsigned char __pad3[4];
+	long long st_size;
+	unsigned int st_blksize;
+	unsigned long long st_blocks;
+	unsigned int st_atime;
+	unsigned int st_atime_nsec;
+	unsigned int st_mtime;
+	unsigned int st_mtime_nsec;
+	unsigned int st_ctime;
+	unsigned int st_ctime_nsec;
+	unsigned long long st_ino;
+} __attribute__((packed));
+
+struct timespec32 {
+	int tv_sec;
+	int tv_nsec;
+};
+
+struct timespec64 {
+	long long tv_sec;
+	long long tv_nsec;
+};
+
+struct timeval32 {
+	int tv_sec;
+	int tv_usec;
+};
+
+struct iovec32 {
+	void *iov_base;
+	unsigned int iov_len;
+};
+
+struct utsname {
+	char sysname[65];
+	char nodename[65];
+	char release[65];
+	char version[65];
+	char machine[65];
+	char domainname[65];
+};
+
+_Static_assert(sizeof(struct stat64) == 96, "the size of stat64 is wrong");
+
+static long syscall6(long nr, long a1, long a2, long a3, long a4, long a5,
+		     long a6)
+{
+	// `ebp` may be used as the frame pointer, so it is saved manually.
+	long args[2] = { a1, a6 };
+	long *args_ptr = args;
+	long ret;
+
+	asm volatile("push %%ebp\n\t"
+		     "mov 4(%%ebx), %%ebp\n\t"
+		     "mov (%%ebx), %%ebx\n\t"
+		     "int $0x80\n\t"
+		     "pop %%ebp"
+		     : "=a"(ret), "+b"(args_ptr)
+		     : "a"(nr), "c"(a2), "d"(a3), "S"(a4), "D"(a5)
+		     : "memory");
+	return ret;
+}
+
+#define syscall0(nr) syscall6(nr, 0, 0, 0, 0, 0, 0)
+#define syscall1(nr, a1) syscall6(nr, (long)(a1), 0, 0, 0, 0, 0)
+#define syscall2(nr, a1, a2) \
+	syscall6(nr, (long)(a1), (long)(a2), 0, 0, 0, 0)
+#define syscall3(nr, a1, a2, a3) \
+	syscall6(nr, (long)(a1), (long)(a2), (long)(a3), 0, 0, 0)
+#define syscall5(nr, a1, a2, a3, a4, a5)                              \
+	syscall6(nr, (long)(a1), (long)(a2), (long)(a3), (long)(a4), \
+		 (long)(a5), 0)
+
+static int failures;
+
+static unsigned int str_len(const char *str)
+{
+	unsigned int len = 0;
+
+	while (str[len] != '\0')
+		len++;
+	return len;
+}
+
+static int str_eq(const char *a, const char *b)
+{
+	while (*a != '\0' && *a == *b) {
+		a++;
+		b++;
+	}
+	return *a == *b;
+}
+
+static void print(const char *str)
+{
+	syscall3(__NR_write, 1, str, str_len(str));
+}
+
+static void print_num(unsigned int num)
+{
+	char buf[11];
+	int pos = sizeof(buf) - 1;
+
+	buf[pos] = '\0';
+	do {
+		buf[--pos] = '0' + num % 10;
+		num /= 10;
+	} while (num != 0);
+	print(&buf[pos]);
+}
+
+#define CHECK(cond)                                    \
+	do {                                           \
+		if (!(cond)) {                         \
+			print(__func__);               \
+			print(": line ");              \
+			print_num(__LINE__);           \
+			print(": " #cond " failed\n"); \
+			failures++;                    \
+		}                                      \
+	} while (0)
+
+static void test_ids(void)
+{
+	long pid = syscall0(__NR_getpid);
+
+	CHECK(pid > 0);
+	CHECK(syscall0(__NR_gettid) == pid);
+}
+
+static void test_uname(void)
+{
+	struct utsname uts;
+
+	CHECK(syscall1(__NR_uname, &uts) == 0);
+	CHECK(str_eq(uts.sysname, "Linux"));
+}
+
+static void test_file(void)
+{
+	struct stat64 st, path_st, link_st;
+	long long offset;
+	char buf[8];
+	struct iovec32 iov[2] = {
+		{ .iov_base = buf, .iov_len = 3 },
+		{ .iov_base = buf + 3, .iov_len = 5 },
+	};
+	long fd;
+
+	fd = syscall3(__NR_open, TEST_FILE, O_RDWR | O_CREAT | O_TRUNC, 0644);
+	CHECK(fd >= 0);
+	CHECK(syscall3(__NR_write, fd, "0123456789", 10) == 10);
+
+	CHECK(syscall2(__NR_fstat64, fd, &st) == 0);
+	CHECK(st.st_size == 10);
+	CHECK((st.st_mode & S_IFMT) == S_IFREG);
+	CHECK((st.st_mode & 0777) == 0644);
+	CHECK(st.__st_ino == (unsigned int)st.st_ino);
+	CHECK(syscall2(__NR_stat64, TEST_FILE, &path_st) == 0);
+	CHECK(path_st.st_ino == st.st_ino && path_st.st_dev == st.st_dev);
+	CHECK(syscall2(__NR_lstat64, TEST_FILE, &link_st) == 0);
+	CHECK(link_st.st_ino == st.st_ino);
+
+	// The 64-bit offset is split into two 32-bit arguments.
+	CHECK(syscall5(__NR__llseek, fd, 0, 4, &offset, SEEK_SET) == 0);
+	CHECK(offset == 4);
+	CHECK(syscall5(__NR__llseek, fd, -1, -2, &offset, SEEK_CUR) == 0);
+	CHECK(offset == 2);
+	CHECK(syscall5(__NR__llseek, fd, -1, -3, &offset, SEEK_CUR) ==
+	      -EINVAL);
+
+	// The offset of `lseek` is signed.
+	CHECK(syscall3(__NR_lseek, fd, -1, SEEK_CUR) == 1);
+	CHECK(syscall3(__NR_lseek, fd, -2, SEEK_CUR) == -EINVAL);
+
+	CHECK(syscall3(__NR_readv, fd, iov, 2) == 8);
+	CHECK(buf[0] == '1' && buf[2] == '3' && buf[3] == '4');
+	CHECK(buf[7] == '8');
+	CHECK(syscall3(__NR_lseek, fd, 0, SEEK_SET) == 0);
+	CHECK(syscall3(__NR_writev, fd, iov, 2) == 8);
+	CHECK(syscall2(__NR_fstat64, fd, &st) == 0);
+	CHECK(st.st_size == 10);
+
+	CHECK(syscall1(__NR_close, fd) == 0);
+	CHECK(syscall1(__NR_unlink, TEST_FILE) == 0);
+}
+
+static void test_mmap(void)
+{
+	char *addr, *file_addr;
+	long long offset;
+	long fd;
+
+	addr = (char *)syscall6(__NR_mmap2, 0, 2 * PAGE_SIZE,
+				PROT_READ | PROT_WRITE,
+				MAP_PRIVATE | MAP_ANONYMOUS, -1, 0);
+	CHECK((unsigned long)addr < -4095UL);
+	addr[0] = 'a';
+	addr[PAGE_SIZE] = 'b';
+
+	// The offset of `mmap2` is in units of 4096 bytes.
+	fd = syscall3(__NR_open, TEST_FILE, O_RDWR | O_CREAT | O_TRUNC, 0644);
+	CHECK(fd >= 0);
+	CHECK(syscall3(__NR_write, fd, addr, 2 * PAGE_SIZE) == 2 * PAGE_SIZE);
+	file_addr = (char *)syscall6(__NR_mmap2, 0, PAGE_SIZE, PROT_READ,
+				     MAP_PRIVATE, fd, 1);
+	CHECK((unsigned long)file_addr < -4095UL);
+	CHECK(file_addr[0] == 'b');
+	CHECK(syscall5(__NR__llseek, fd, 0, 0, &offset, SEEK_CUR) == 0);
+	CHECK(offset == 2 * PAGE_SIZE);
+
+	CHECK(syscall2(__NR_munmap, file_addr, PAGE_SIZE) == 0);
+	CHECK(syscall2(__NR_munmap, addr, 2 * PAGE_SIZE) == 0);
+	CHECK(syscall1(__NR_close, fd) == 0);
+	CHECK(syscall1(__NR_unlink, TEST_FILE) == 0);
+}
+
+static void test_brk(void)
+{
+	unsigned long heap_start = syscall1(__NR_brk, 0);
+	unsigned long heap_end = heap_start + PAGE_SIZE;
+
+	CHECK(syscall1(__NR_brk, heap_end) == (long)heap_end);
+	((char *)heap_start)[PAGE_SIZE - 1] = 'c';
+	CHECK(syscall1(__NR_brk, heap_start) == (long)heap_start);
+}
+
+static void test_time(void)
+{
+	struct timespec32 ts;
+	struct timespec64 ts64;
+	struct timeval32 tv;
+	int tloc;
+	long now;
+
+	CHECK(syscall2(__NR_clock_gettime, CLOCK_MONOTONIC, &ts) == 0);
+	CHECK(ts.tv_nsec >= 0 && ts.tv_nsec < 1000000000);
+	CHECK(syscall2(__NR_clock_gettime64, CLOCK_MONOTONIC, &ts64) == 0);
+	CHECK(ts64.tv_nsec >= 0 && ts64.tv_nsec < 1000000000);
+	CHECK(ts64.tv_sec >= ts.tv_sec);
+
+	CHECK(syscall1(__NR_gettimeofday, &tv) == 0);
+	CHECK(tv.tv_usec >= 0 && tv.tv_usec < 1000000);
+	now = syscall1(__NR_time, &tloc);
+	CHECK(now == tloc);
+	CHECK(now >= tv.tv_sec);
+
+	ts.tv_sec = 0;
+	ts.tv_nsec = 1000000;
+	CHECK(syscall2(__NR_nanosleep, &ts, 0) == 0);
+	ts.tv_nsec = -1;
+	CHECK(syscall2(__NR_nanosleep, &ts, 0) == -EINVAL);
+}
+
+static void test_ioctl(void)
+{
+	int fds[2];
+	int is_nonblocking = 1;
+	char buf[1];
+
+	CHECK(syscall1(__NR_pipe, fds) == 0);
+	CHECK(syscall3(__NR_ioctl, fds[0], FIONBIO, &is_nonblocking) == 0);
+	CHECK(syscall3(__NR_read, fds[0], buf, 1) == -EAGAIN);
+
+	// The commands whose arguments differ for 32-bit programs are rejected.
+	CHECK(syscall3(__NR_ioctl, fds[0], TUNSETIFF, 0) == -ENOTTY);
+
+	CHECK(syscall1(__NR_close, fds[0]) == 0);
+	CHECK(syscall1(__NR_close, fds[1]) == 0);
+}
+
+static void test_fork(void)
+{
+	int status;
+	long pid;
+
+	pid = syscall0(__NR_fork);
+	if (pid == 0)
+		syscall1(__NR_exit, 7);
+	CHECK(pid > 0);
+	CHECK(syscall3(__NR_waitpid, pid, &status, 0) == pid);
+	CHECK(status == 7 << 8);
+}
+
+__attribute__((force_align_arg_pointer)) void _start(void)
+{
+	test_ids();
+	test_uname();
+	test_file();
+	test_mmap();
+	test_brk();
+	test_time();
+	test_ioctl();
+	test_fork();
+
+	if (failures == 0)
+		print("All ia32 tests passed\n");
+	syscall1(__NR_exit_group, failures != 0);
+	__builtin_unreachable();
+}
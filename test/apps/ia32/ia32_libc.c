@@ -0,0 +1,212 @@
+// SPDX-License-Identifier: MPL-2.0
+
+// A 32-bit (i386) program that uses the C library, which sets up the
+// thread-local storage with `set_thread_area` and runs the signal handlers on
+// the 32-bit signal frames.
+
+#define _GNU_SOURCE
+
+#include <asm/ldt.h>
+#include <signal.h>
+#include <stdint.h>
+#include <sys/mman.h>
+#include <sys/syscall.h>
+#include <unistd.h>
+
+#include "../network/test.h"
+
+#define GDT_ENTRY_TLS_MIN 12
+#define GDT_ENTRY_TLS_MAX 14
+
+static __thread int tls_var = 42;
+
+static unsigned int read_gs(void)
+{
+	unsigned int gs;
+
+	asm volatile("mov %%gs, %0" : "=r"(gs));
+	return gs;
+}
+
+static uintptr_t read_tcb(void)
+{
+	uintptr_t tcb;
+
+	// The first word of the thread control block points to itself.
+	asm volatile("movl %%gs:0, %0" : "=r"(tcb));
+	return tcb;
+}
+
+FN_TEST(thread_local_storage)
+{
+	struct user_desc desc = { 0 };
+	unsigned int gs = read_gs();
+
+	TEST_RES(tls_var, _ret == 42);
+	tls_var = 43;
+	TEST_RES(tls_var, _ret == 43);
+
+	// The C library loads the GS selector of the segment that it sets up.
+	TEST_RES(gs >> 3,
+		 _ret >= GDT_ENTRY_TLS_MIN && _ret <= GDT_ENTRY_TLS_MAX);
+	desc.entry_number = gs >> 3;
+	TEST_RES(syscall(SYS_get_thread_area, &desc),
+		 _ret == 0 && desc.base_addr == read_tcb() &&
+			 desc.seg_32bit == 1 && desc.seg_not_present == 0);
+}
+END_TEST()
+
+FN_TEST(set_thread_area)
+{
+	static int data = 1234;
+	struct user_desc desc = { .entry_number = -1,
+				  .base_addr = (uintptr_t)&data,
+				  .limit = 0xfffff,
+				  .seg_32bit = 1,
+				  .limit_in_pages = 1,
+				  .useable = 1 };
+	unsigned int entry, value, gs = read_gs();
+
+	// A free entry is allocated.
+	TEST_RES(syscall(SYS_set_thread_area, &desc),
+		 _ret == 0 && desc.entry_number >= GDT_ENTRY_TLS_MIN &&
+			 desc.entry_number <= GDT_ENTRY_TLS_MAX &&
+			 desc.entry_number != gs >> 3);
+	entry = desc.entry_number;
+
+	// The segment can be used with the GS segment register. The C library
+	// cannot be used until the GS segment register is restored.
+	asm volatile("mov %1, %%gs\n\t"
+		     "movl %%gs:0, %0\n\t"
+		     "mov %2, %%gs"
+		     : "=&r"(value)
+		     : "r"(entry << 3 | 3), "r"(gs));
+	TEST_RES(value, _ret == 1234);
+
+	memset(&desc, 0, sizeof(desc));
+	desc.entry_number = entry;
+	TEST_RES(syscall(SYS_get_thread_area, &desc),
+		 _ret == 0 && desc.base_addr == (uintptr_t)&data &&
+			 desc.limit == 0xfffff && desc.limit_in_pages == 1 &&
+			 desc.useable == 1 && desc.read_exec_only == 0);
+
+	// An empty description clears the segment.
+	memset(&desc, 0, sizeof(desc));
+	desc.entry_number = entry;
+	TEST_SUCC(syscall(SYS_set_thread_area, &desc));
+	TEST_RES(syscall(SYS_get_thread_area, &desc),
+		 _ret == 0 && desc.base_addr == 0 &&
+			 desc.seg_not_present == 1 &&
+			 desc.read_exec_only == 1);
+
+	// Only the thread-local storage entries can be set.
+	desc.entry_number = GDT_ENTRY_TLS_MIN - 1;
+	TEST_ERRNO(syscall(SYS_set_thread_area, &desc), EINVAL);
+	TEST_ERRNO(syscall(SYS_get_thread_area, &desc), EINVAL);
+
+	// Only the data segments can be set.
+	desc.entry_number = entry;
+	desc.seg_32bit = 1;
+	desc.contents = MODIFY_LDT_CONTENTS_CODE;
+	TEST_ERRNO(syscall(SYS_set_thread_area, &desc), EINVAL);
+}
+END_TEST()
+
+static volatile int handled_sig;
+static volatile int handled_code;
+static volatile pid_t handled_pid;
+static volatile int is_masked;
+static volatile int handled_tls_var;
+
+static void siginfo_handler(int sig, siginfo_t *info, void *ucontext)
+{
+	sigset_t mask;
+
+	handled_sig = info->si_signo;
+	handled_code = info->si_code;
+	handled_pid = info->si_pid;
+	sigprocmask(SIG_BLOCK, NULL, &mask);
+	is_masked = sigismember(&mask, sig);
+	handled_tls_var = tls_var;
+}
+
+FN_TEST(rt_sigframe)
+{
+	struct sigaction sa = { .sa_sigaction = siginfo_handler,
+				.sa_flags = SA_SIGINFO };
+	sigset_t mask;
+
+	tls_var = 44;
+	TEST_SUCC(sigaction(SIGUSR1, &sa, NULL));
+	TEST_SUCC(raise(SIGUSR1));
+	TEST_RES(handled_sig, _ret == SIGUSR1);
+	TEST_RES(handled_code, _ret == SI_TKILL);
+	TEST_RES(handled_pid, _ret == getpid());
+	TEST_RES(is_masked, _ret == 1);
+	TEST_RES(handled_tls_var, _ret == 44);
+
+	// The signal mask is restored by `rt_sigreturn`.
+	TEST_SUCC(sigprocmask(SIG_BLOCK, NULL, &mask));
+	TEST_RES(sigismember(&mask, SIGUSR1), _ret == 0);
+	TEST_RES(tls_var, _ret == 44);
+}
+END_TEST()
+
+static volatile int nr_handled;
+
+static void handler(int sig)
+{
+	sigset_t mask;
+
+	nr_handled++;
+	sigprocmask(SIG_BLOCK, NULL, &mask);
+	is_masked = sigismember(&mask, sig);
+	handled_tls_var = tls_var;
+}
+
+FN_TEST(sigframe)
+{
+	struct sigaction sa = { .sa_handler = handler };
+	sigset_t mask;
+
+	tls_var = 45;
+	TEST_SUCC(sigaction(SIGUSR2, &sa, NULL));
+	TEST_SUCC(raise(SIGUSR2));
+	TEST_SUCC(raise(SIGUSR2));
+	TEST_RES(nr_handled, _ret == 2);
+	TEST_RES(is_masked, _ret == 1);
+	TEST_RES(handled_tls_var, _ret == 45);
+
+	// The signal mask is restored by `sigreturn`.
+	TEST_SUCC(sigprocmask(SIG_BLOCK, NULL, &mask));
+	TEST_RES(sigismember(&mask, SIGUSR2), _ret == 0);
+}
+END_TEST()
+
+static char *fault_page;
+static volatile uintptr_t fault_addr;
+
+static void segv_handler(int sig, siginfo_t *info, void *ucontext)
+{
+	fault_addr = (uintptr_t)info->si_addr;
+	// The faulting instruction is executed again after the handler returns.
+	mprotect(fault_page, getpagesize(), PROT_READ | PROT_WRITE);
+}
+
+FN_TEST(fault)
+{
+	struct sigaction sa = { .sa_sigaction = segv_handler,
+				.sa_flags = SA_SIGINFO };
+
+	fault_page = mmap(NULL, getpagesize(), PROT_NONE,
+			  MAP_PRIVATE | MAP_ANONYMOUS, -1, 0);
+	TEST_RES(fault_page == MAP_FAILED ? -1 : 0, _ret == 0);
+	TEST_SUCC(sigaction(SIGSEGV, &sa, NULL));
+
+	fault_page[8] = 'a';
+	TEST_RES(fault_addr, _ret == (uintptr_t)fault_page + 8);
+	TEST_RES(fault_page[8], _ret == 'a');
+
+	TEST_SUCC(munmap(fault_page, getpagesize()));
+}
+END_TEST()
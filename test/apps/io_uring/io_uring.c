@@ -0,0 +1,315 @@
+// SPDX-License-Identifier: MPL-2.0
+
+#define _GNU_SOURCE
+
+#include <linux/io_uring.h>
+#include <linux/time_types.h>
+#include <signal.h>
+#include <string.h>
+#include <sys/mman.h>
+#include <sys/syscall.h>
+#include <sys/wait.h>
+#include <unistd.h>
+
+#include "../network/test.h"
+
+#define ENTRIES 4
+
+static int ring_fd;
+static struct io_uring_params params;
+static void *ring_ptr;
+static struct io_uring_sqe *sqes;
+static int rfd, wfd;
+
+static int io_uring_setup(unsigned int entries, struct io_uring_params *p)
+{
+	return syscall(__NR_io_uring_setup, entries, p);
+}
+
+static int io_uring_enter(int fd, unsigned int to_submit,
+			  unsigned int min_complete, unsigned int flags)
+{
+	return syscall(__NR_io_uring_enter, fd, to_submit, min_complete, flags,
+		       NULL, 0);
+}
+
+#define RING_U32(off) ((unsigned int *)((char *)ring_ptr + (off)))
+
+static struct io_uring_sqe *get_sqe(void)
+{
+	unsigned int tail = *RING_U32(params.sq_off.tail);
+	unsigned int index = tail & *RING_U32(params.sq_off.ring_mask);
+	struct io_uring_sqe *sqe = &sqes[index];
+
+	memset(sqe, 0, sizeof(*sqe));
+	RING_U32(params.sq_off.array)[index] = index;
+	__atomic_store_n(RING_U32(params.sq_off.tail), tail + 1,
+			 __ATOMIC_RELEASE);
+
+	return sqe;
+}
+
+static int pop_cqe(struct io_uring_cqe *cqe)
+{
+	unsigned int head = *RING_U32(params.cq_off.head);
+	unsigned int tail =
+		__atomic_load_n(RING_U32(params.cq_off.tail), __ATOMIC_ACQUIRE);
+	struct io_uring_cqe *cqes =
+		(struct io_uring_cqe *)((char *)ring_ptr + params.cq_off.cqes);
+
+	if (head == tail)
+		return -1;
+
+	*cqe = cqes[head & *RING_U32(params.cq_off.ring_mask)];
+	__atomic_store_n(RING_U32(params.cq_off.head), head + 1,
+			 __ATOMIC_RELEASE);
+	return 0;
+}
+
+FN_SETUP(io_uring)
+{
+	int fildes[2];
+	size_t ring_size;
+
+	ring_fd = CHECK(io_uring_setup(ENTRIES, &params));
+
+	ring_size = params.cq_off.cqes +
+		    params.cq_entries * sizeof(struct io_uring_cqe);
+	if (ring_size < params.sq_off.array + params.sq_entries * 4)
+		ring_size = params.sq_off.array + params.sq_entries * 4;
+
+	ring_ptr = mmap(NULL, ring_size, PROT_READ | PROT_WRITE, MAP_SHARED,
+			ring_fd, IORING_OFF_SQ_RING);
+	CHECK(ring_ptr == MAP_FAILED ? -1 : 0);
+
+	sqes = mmap(NULL, params.sq_entries * sizeof(struct io_uring_sqe),
+		    PROT_READ | PROT_WRITE, MAP_SHARED, ring_fd, IORING_OFF_SQES);
+	CHECK(sqes == MAP_FAILED ? -1 : 0);
+
+	CHECK(pipe(fildes));
+	rfd = fildes[0];
+	wfd = fildes[1];
+}
+END_SETUP()
+
+FN_TEST(setup)
+{
+	struct io_uring_params p;
+
+	TEST_RES(params.sq_entries, _ret == ENTRIES);
+	TEST_RES(params.cq_entries, _ret == 2 * ENTRIES);
+	TEST_RES(params.features, _ret & IORING_FEAT_SINGLE_MMAP);
+	TEST_RES(*RING_U32(params.sq_off.ring_entries), _ret == ENTRIES);
+	TEST_RES(*RING_U32(params.cq_off.ring_entries), _ret == 2 * ENTRIES);
+
+	memset(&p, 0, sizeof(p));
+	TEST_ERRNO(io_uring_setup(0, &p), EINVAL);
+
+	memset(&p, 0, sizeof(p));
+	p.resv[0] = 1;
+	TEST_ERRNO(io_uring_setup(ENTRIES, &p), EINVAL);
+
+	memset(&p, 0, sizeof(p));
+	p.flags = IORING_SETUP_CQSIZE;
+	p.cq_entries = 3;
+	TEST_RES(io_uring_setup(3, &p),
+		 p.sq_entries == 4 && p.cq_entries == 4 && close(_ret) == 0);
+
+	TEST_ERRNO(io_uring_enter(rfd, 0, 0, 0), EOPNOTSUPP);
+	TEST_ERRNO(io_uring_enter(ring_fd, 0, 0, 1U << 31), EINVAL);
+}
+END_TEST()
+
+FN_TEST(nop)
+{
+	struct io_uring_sqe *sqe;
+	struct io_uring_cqe cqe;
+
+	sqe = get_sqe();
+	sqe->opcode = IORING_OP_NOP;
+	sqe->user_data = 42;
+
+	TEST_RES(io_uring_enter(ring_fd, 1, 1, IORING_ENTER_GETEVENTS),
+		 _ret == 1);
+	TEST_RES(pop_cqe(&cqe),
+		 _ret == 0 && cqe.user_data == 42 && cqe.res == 0);
+	TEST_RES(pop_cqe(&cqe), _ret == -1);
+}
+END_TEST()
+
+FN_TEST(invalid_sqe)
+{
+	struct io_uring_sqe *sqe;
+	struct io_uring_cqe cqe;
+
+	sqe = get_sqe();
+	sqe->opcode = 0xff;
+	sqe->user_data = 1;
+
+	sqe = get_sqe();
+	sqe->opcode = IORING_OP_READ;
+	sqe->fd = 1000;
+	sqe->user_data = 2;
+
+	TEST_RES(io_uring_enter(ring_fd, 2, 2, IORING_ENTER_GETEVENTS),
+		 _ret == 2);
+	TEST_RES(pop_cqe(&cqe),
+		 _ret == 0 && cqe.user_data == 1 && cqe.res == -EINVAL);
+	TEST_RES(pop_cqe(&cqe),
+		 _ret == 0 && cqe.user_data == 2 && cqe.res == -EBADF);
+}
+END_TEST()
+
+FN_TEST(write_read)
+{
+	struct io_uring_sqe *sqe;
+	struct io_uring_cqe cqe;
+	char buf[16] = { 0 };
+
+	sqe = get_sqe();
+	sqe->opcode = IORING_OP_WRITE;
+	sqe->fd = wfd;
+	sqe->off = -1;
+	sqe->addr = (unsigned long)"hello";
+	sqe->len = 5;
+	sqe->user_data = 3;
+
+	TEST_RES(io_uring_enter(ring_fd, 1, 1, IORING_ENTER_GETEVENTS),
+		 _ret == 1);
+	TEST_RES(pop_cqe(&cqe),
+		 _ret == 0 && cqe.user_data == 3 && cqe.res == 5);
+
+	sqe = get_sqe();
+	sqe->opcode = IORING_OP_READ;
+	sqe->fd = rfd;
+	sqe->off = -1;
+	sqe->addr = (unsigned long)buf;
+	sqe->len = sizeof(buf);
+	sqe->user_data = 4;
+
+	TEST_RES(io_uring_enter(ring_fd, 1, 1, IORING_ENTER_GETEVENTS),
+		 _ret == 1);
+	TEST_RES(pop_cqe(&cqe), _ret == 0 && cqe.user_data == 4 &&
+					cqe.res == 5 &&
+					memcmp(buf, "hello", 5) == 0);
+}
+END_TEST()
+
+FN_TEST(async_read)
+{
+	struct io_uring_sqe *sqe;
+	struct io_uring_cqe cqe;
+	char buf[16] = { 0 };
+
+	// The read blocks in a worker thread until the data arrives.
+	sqe = get_sqe();
+	sqe->opcode = IORING_OP_READ;
+	sqe->fd = rfd;
+	sqe->off = -1;
+	sqe->addr = (unsigned long)buf;
+	sqe->len = sizeof(buf);
+	sqe->user_data = 5;
+
+	TEST_RES(io_uring_enter(ring_fd, 1, 0, 0), _ret == 1);
+	TEST_RES(pop_cqe(&cqe), _ret == -1);
+
+	TEST_RES(write(wfd, "world", 5), _ret == 5);
+
+	TEST_RES(io_uring_enter(ring_fd, 0, 1, IORING_ENTER_GETEVENTS),
+		 _ret == 0);
+	TEST_RES(pop_cqe(&cqe), _ret == 0 && cqe.user_data == 5 &&
+					cqe.res == 5 &&
+					memcmp(buf, "world", 5) == 0);
+}
+END_TEST()
+
+FN_TEST(timeout)
+{
+	struct io_uring_sqe *sqe;
+	struct io_uring_cqe cqe;
+	struct __kernel_timespec ts = { .tv_sec = 0, .tv_nsec = 10000000 };
+
+	sqe = get_sqe();
+	sqe->opcode = IORING_OP_TIMEOUT;
+	sqe->addr = (unsigned long)&ts;
+	sqe->len = 1;
+	sqe->user_data = 6;
+
+	TEST_RES(io_uring_enter(ring_fd, 1, 1, IORING_ENTER_GETEVENTS),
+		 _ret == 1);
+	TEST_RES(pop_cqe(&cqe),
+		 _ret == 0 && cqe.user_data == 6 && cqe.res == -ETIME);
+}
+END_TEST()
+
+FN_TEST(deliver_in_other_process)
+{
+	struct io_uring_sqe *sqe;
+	struct io_uring_cqe cqe;
+	char buf[16] = { 0 };
+	int status;
+	pid_t pid;
+
+	TEST_RES(write(wfd, "child", 5), _ret == 5);
+
+	sqe = get_sqe();
+	sqe->opcode = IORING_OP_READ;
+	sqe->fd = rfd;
+	sqe->off = -1;
+	sqe->addr = (unsigned long)buf;
+	sqe->len = sizeof(buf);
+	sqe->user_data = 7;
+
+	TEST_RES(io_uring_enter(ring_fd, 1, 0, 0), _ret == 1);
+
+	// The read data cannot be delivered to the address space of the child.
+	pid = TEST_SUCC(fork());
+	if (pid == 0)
+		_exit(io_uring_enter(ring_fd, 0, 1, IORING_ENTER_GETEVENTS));
+	TEST_RES(waitpid(pid, &status, 0),
+		 _ret == pid && WIFEXITED(status) && WEXITSTATUS(status) == 0);
+
+	TEST_RES(pop_cqe(&cqe), _ret == 0 && cqe.user_data == 7 &&
+					cqe.res == -ECANCELED && buf[0] == 0);
+}
+END_TEST()
+
+FN_TEST(cancel_on_close)
+{
+	struct io_uring_sqe *sqe;
+	static char buf[16];
+	int fildes[2];
+	int i;
+
+	TEST_SUCC(pipe(fildes));
+	signal(SIGPIPE, SIG_IGN);
+
+	// The read blocks in a worker thread since no data arrives.
+	sqe = get_sqe();
+	sqe->opcode = IORING_OP_READ;
+	sqe->fd = fildes[0];
+	sqe->off = -1;
+	sqe->addr = (unsigned long)buf;
+	sqe->len = sizeof(buf);
+	sqe->user_data = 8;
+
+	TEST_RES(io_uring_enter(ring_fd, 1, 0, 0), _ret == 1);
+
+	// Closing the instance cancels the read, which releases the read end.
+	TEST_SUCC(close(ring_fd));
+	TEST_SUCC(close(fildes[0]));
+	for (i = 0; i < 100 && write(fildes[1], "x", 1) == 1; ++i)
+		usleep(10000);
+	TEST_ERRNO(write(fildes[1], "x", 1), EPIPE);
+
+	TEST_SUCC(close(fildes[1]));
+	signal(SIGPIPE, SIG_DFL);
+}
+END_TEST()
+
+FN_SETUP(cleanup)
+{
+	CHECK(close(rfd));
+	CHECK(close(wfd));
+}
+END_SETUP()
@@ -0,0 +1,255 @@
+// SPDX-License-Identifier: MPL-2.0
+
+#define _GNU_SOURCE
+
+#include <signal.h>
+#include <stdint.h>
+#include <string.h>
+#include <sys/syscall.h>
+#include <sys/time.h>
+#include <time.h>
+#include <unistd.h>
+
+#include "../network/test.h"
+
+static volatile int caught_signo;
+static volatile int caught_code;
+static volatile int caught_timerid;
+static volatile int caught_overrun;
+static volatile int caught_value;
+
+static void handler(int signo, siginfo_t *info, void *ucontext)
+{
+	caught_signo = signo;
+	caught_code = info->si_code;
+	caught_timerid = info->si_timerid;
+	caught_overrun = info->si_overrun;
+	caught_value = info->si_value.sival_int;
+}
+
+static sigset_t timer_sigs;
+
+FN_SETUP(handlers)
+{
+	struct sigaction sa;
+
+	memset(&sa, 0, sizeof(sa));
+	sa.sa_sigaction = handler;
+	sa.sa_flags = SA_SIGINFO;
+	CHECK(sigaction(SIGALRM, &sa, NULL));
+	CHECK(sigaction(SIGRTMIN, &sa, NULL));
+	CHECK(sigaction(SIGPROF, &sa, NULL));
+
+	// The signals are blocked until the tests are ready to receive them.
+	CHECK(sigemptyset(&timer_sigs));
+	CHECK(sigaddset(&timer_sigs, SIGALRM));
+	CHECK(sigaddset(&timer_sigs, SIGRTMIN));
+	CHECK(sigaddset(&timer_sigs, SIGPROF));
+	CHECK(sigprocmask(SIG_BLOCK, &timer_sigs, NULL));
+}
+END_SETUP()
+
+static void arm_timer(timer_t timerid, long value_ns, long interval_ns)
+{
+	struct itimerspec its = {
+		.it_value = { .tv_nsec = value_ns },
+		.it_interval = { .tv_nsec = interval_ns },
+	};
+
+	CHECK(timer_settime(timerid, 0, &its, NULL));
+}
+
+static int is_pending(int signo)
+{
+	sigset_t set;
+
+	CHECK(sigpending(&set));
+	return sigismember(&set, signo);
+}
+
+// Unblocks the pending signals, so the handler runs and records the signal information.
+static void receive_signals(void)
+{
+	caught_signo = 0;
+	CHECK(sigprocmask(SIG_UNBLOCK, &timer_sigs, NULL));
+	CHECK(sigprocmask(SIG_BLOCK, &timer_sigs, NULL));
+}
+
+static int kernel_timer_id(timer_t timerid)
+{
+	return (int)(intptr_t)timerid;
+}
+
+FN_TEST(sigev_signal)
+{
+	struct sigevent sev = {
+		.sigev_notify = SIGEV_SIGNAL,
+		.sigev_signo = SIGRTMIN,
+		.sigev_value.sival_int = 42,
+	};
+	timer_t timerid;
+
+	TEST_SUCC(timer_create(CLOCK_MONOTONIC, &sev, &timerid));
+	arm_timer(timerid, 10000000, 0);
+	TEST_SUCC(usleep(50000));
+
+	TEST_RES(is_pending(SIGRTMIN), _ret == 1);
+	receive_signals();
+	TEST_RES(caught_signo, _ret == SIGRTMIN && caught_code == SI_TIMER &&
+				       caught_value == 42 &&
+				       caught_timerid == kernel_timer_id(timerid) &&
+				       caught_overrun == 0);
+
+	TEST_SUCC(timer_delete(timerid));
+}
+END_TEST()
+
+FN_TEST(default_sigevent)
+{
+	struct itimerspec its = { .it_value = { .tv_nsec = 10000000 } };
+	int timerid;
+
+	// The C library never passes a NULL `sigevent`, so the system calls are used directly.
+	TEST_SUCC(syscall(SYS_timer_create, CLOCK_REALTIME, NULL, &timerid));
+	TEST_SUCC(syscall(SYS_timer_settime, timerid, 0, &its, NULL));
+	TEST_SUCC(usleep(50000));
+
+	receive_signals();
+	TEST_RES(caught_signo, _ret == SIGALRM && caught_code == SI_TIMER &&
+				       caught_value == timerid);
+
+	TEST_SUCC(syscall(SYS_timer_delete, timerid));
+}
+END_TEST()
+
+FN_TEST(sigev_thread_id)
+{
+	struct sigevent sev = {
+		.sigev_notify = SIGEV_THREAD_ID,
+		.sigev_signo = SIGRTMIN,
+		.sigev_value.sival_int = 7,
+	};
+	timer_t timerid;
+
+	sev._sigev_un._tid = syscall(SYS_gettid);
+	TEST_SUCC(timer_create(CLOCK_MONOTONIC, &sev, &timerid));
+	arm_timer(timerid, 10000000, 0);
+	TEST_SUCC(usleep(50000));
+
+	receive_signals();
+	TEST_RES(caught_signo, _ret == SIGRTMIN && caught_code == SI_TIMER &&
+				       caught_value == 7);
+
+	TEST_SUCC(timer_delete(timerid));
+
+	sev._sigev_un._tid = 0x7fffffff;
+	TEST_ERRNO(timer_create(CLOCK_MONOTONIC, &sev, &timerid), EINVAL);
+}
+END_TEST()
+
+FN_TEST(overrun)
+{
+	struct sigevent sev = {
+		.sigev_notify = SIGEV_SIGNAL,
+		.sigev_signo = SIGRTMIN,
+	};
+	timer_t timerid;
+	int overrun;
+
+	TEST_SUCC(timer_create(CLOCK_MONOTONIC, &sev, &timerid));
+	TEST_RES(timer_getoverrun(timerid), _ret == 0);
+
+	// Only one signal is queued while the signal is blocked, and the other expirations are
+	// counted as overruns.
+	arm_timer(timerid, 10000000, 10000000);
+	TEST_SUCC(usleep(200000));
+
+	receive_signals();
+	overrun = caught_overrun;
+	TEST_RES(caught_signo, _ret == SIGRTMIN && overrun >= 5);
+	TEST_RES(timer_getoverrun(timerid), _ret == overrun);
+
+	TEST_SUCC(timer_delete(timerid));
+	TEST_ERRNO(timer_getoverrun(timerid), EINVAL);
+}
+END_TEST()
+
+static long long cpu_time_ns(void)
+{
+	struct timespec ts;
+
+	CHECK(clock_gettime(CLOCK_PROCESS_CPUTIME_ID, &ts));
+	return ts.tv_sec * 1000000000LL + ts.tv_nsec;
+}
+
+// Burns the CPU until the signal is pending or the CPU time exceeds the limit.
+static long long burn_cpu_until_pending(int signo, long long limit_ns)
+{
+	long long start = cpu_time_ns();
+	long long elapsed;
+
+	do {
+		elapsed = cpu_time_ns() - start;
+	} while (!is_pending(signo) && elapsed < limit_ns);
+
+	return elapsed;
+}
+
+FN_TEST(cpu_time_clock)
+{
+	struct sigevent sev = {
+		.sigev_notify = SIGEV_SIGNAL,
+		.sigev_signo = SIGRTMIN,
+	};
+	timer_t timerid;
+	clockid_t clockid;
+
+	// The C library converts `CLOCK_PROCESS_CPUTIME_ID` to a dynamic clock ID.
+	TEST_SUCC(timer_create(CLOCK_PROCESS_CPUTIME_ID, &sev, &timerid));
+	arm_timer(timerid, 50000000, 0);
+	TEST_RES(burn_cpu_until_pending(SIGRTMIN, 2000000000LL),
+		 _ret >= 40000000 && _ret < 2000000000LL);
+	receive_signals();
+	TEST_RES(caught_signo, _ret == SIGRTMIN && caught_code == SI_TIMER);
+	TEST_SUCC(timer_delete(timerid));
+
+	TEST_SUCC(clock_getcpuclockid(getpid(), &clockid));
+	TEST_SUCC(timer_create(clockid, &sev, &timerid));
+	TEST_SUCC(timer_delete(timerid));
+}
+END_TEST()
+
+FN_TEST(itimer_prof)
+{
+	struct itimerval itv = { .it_value = { .tv_usec = 20000 } };
+
+	TEST_SUCC(setitimer(ITIMER_PROF, &itv, NULL));
+	TEST_RES(burn_cpu_until_pending(SIGPROF, 2000000000LL),
+		 _ret < 2000000000LL);
+	TEST_RES(is_pending(SIGALRM), _ret == 0);
+	receive_signals();
+	TEST_RES(caught_signo, _ret == SIGPROF);
+}
+END_TEST()
+
+FN_TEST(invalid_args)
+{
+	struct sigevent sev = {
+		.sigev_notify = SIGEV_SIGNAL,
+		.sigev_signo = 0,
+	};
+	timer_t timerid;
+
+	TEST_ERRNO(timer_create(CLOCK_MONOTONIC, &sev, &timerid), EINVAL);
+	sev.sigev_signo = 65;
+	TEST_ERRNO(timer_create(CLOCK_MONOTONIC, &sev, &timerid), EINVAL);
+	sev.sigev_signo = SIGRTMIN;
+	TEST_ERRNO(timer_create(CLOCK_MONOTONIC_RAW, &sev, &timerid),
+		   EOPNOTSUPP);
+	TEST_ERRNO(timer_create(100, &sev, &timerid), EINVAL);
+
+	TEST_SUCC(timer_create(CLOCK_MONOTONIC, &sev, &timerid));
+	TEST_SUCC(timer_delete(timerid));
+	TEST_ERRNO(timer_delete(timerid), EINVAL);
+}
+END_TEST()
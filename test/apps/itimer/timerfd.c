@@ -0,0 +1,156 @@
+// SPDX-License-Identifier: MPL-2.0
+
+#define _GNU_SOURCE
+
+#include <fcntl.h>
+#include <poll.h>
+#include <stdint.h>
+#include <sys/timerfd.h>
+#include <time.h>
+#include <unistd.h>
+
+#include "../network/test.h"
+
+static int tfd;
+
+FN_SETUP(create)
+{
+	tfd = CHECK(timerfd_create(CLOCK_MONOTONIC, TFD_NONBLOCK | TFD_CLOEXEC));
+}
+END_SETUP()
+
+FN_TEST(create_flags)
+{
+	TEST_RES(fcntl(tfd, F_GETFD), _ret == FD_CLOEXEC);
+	TEST_RES(fcntl(tfd, F_GETFL), (_ret & O_NONBLOCK) != 0);
+
+	TEST_ERRNO(timerfd_create(CLOCK_MONOTONIC, 0x1), EINVAL);
+	TEST_ERRNO(timerfd_create(-1, 0), EINVAL);
+}
+END_TEST()
+
+FN_TEST(disarmed)
+{
+	struct itimerspec its;
+	uint64_t ticks;
+
+	TEST_RES(timerfd_gettime(tfd, &its),
+		 its.it_value.tv_sec == 0 && its.it_value.tv_nsec == 0);
+	TEST_ERRNO(read(tfd, &ticks, sizeof(ticks)), EAGAIN);
+}
+END_TEST()
+
+FN_TEST(relative)
+{
+	struct itimerspec its = { .it_value = { .tv_nsec = 100 * 1000 * 1000 } };
+	struct itimerspec old;
+	struct pollfd pfd = { .fd = tfd, .events = POLLIN };
+	uint64_t ticks;
+
+	TEST_SUCC(timerfd_settime(tfd, 0, &its, NULL));
+	TEST_RES(timerfd_gettime(tfd, &old),
+		 old.it_value.tv_sec == 0 && old.it_value.tv_nsec > 0);
+	TEST_ERRNO(read(tfd, &ticks, sizeof(ticks)), EAGAIN);
+
+	TEST_RES(poll(&pfd, 1, 1000), _ret == 1 && pfd.revents == POLLIN);
+	TEST_ERRNO(read(tfd, &ticks, sizeof(ticks) - 1), EINVAL);
+	TEST_RES(read(tfd, &ticks, sizeof(ticks)),
+		 _ret == sizeof(ticks) && ticks == 1);
+	TEST_ERRNO(read(tfd, &ticks, sizeof(ticks)), EAGAIN);
+}
+END_TEST()
+
+FN_TEST(interval)
+{
+	struct itimerspec its = {
+		.it_interval = { .tv_nsec = 10 * 1000 * 1000 },
+		.it_value = { .tv_nsec = 10 * 1000 * 1000 },
+	};
+	struct itimerspec old;
+	uint64_t ticks;
+
+	TEST_SUCC(timerfd_settime(tfd, 0, &its, NULL));
+	TEST_SUCC(usleep(100 * 1000));
+	TEST_RES(read(tfd, &ticks, sizeof(ticks)),
+		 _ret == sizeof(ticks) && ticks > 1);
+
+	its.it_value.tv_nsec = 0;
+	TEST_RES(timerfd_settime(tfd, 0, &its, &old),
+		 old.it_interval.tv_nsec == 10 * 1000 * 1000);
+	TEST_RES(timerfd_gettime(tfd, &old),
+		 old.it_value.tv_sec == 0 && old.it_value.tv_nsec == 0);
+	TEST_ERRNO(read(tfd, &ticks, sizeof(ticks)), EAGAIN);
+}
+END_TEST()
+
+FN_TEST(absolute)
+{
+	struct itimerspec its = { 0 };
+	struct pollfd pfd = { .fd = tfd, .events = POLLIN };
+	uint64_t ticks;
+
+	TEST_SUCC(clock_gettime(CLOCK_MONOTONIC, &its.it_value));
+	its.it_value.tv_sec += 1;
+
+	TEST_SUCC(timerfd_settime(tfd, TFD_TIMER_ABSTIME, &its, NULL));
+	TEST_RES(poll(&pfd, 1, 0), _ret == 0);
+	TEST_RES(poll(&pfd, 1, 2000), _ret == 1 && pfd.revents == POLLIN);
+	TEST_RES(read(tfd, &ticks, sizeof(ticks)), ticks == 1);
+
+	// An expiration time in the past fires immediately.
+	TEST_SUCC(timerfd_settime(tfd, TFD_TIMER_ABSTIME, &its, NULL));
+	TEST_RES(poll(&pfd, 1, 1000), _ret == 1);
+	TEST_RES(read(tfd, &ticks, sizeof(ticks)), ticks == 1);
+}
+END_TEST()
+
+FN_TEST(cancel_on_set)
+{
+	struct itimerspec its = { .it_value = { .tv_sec = 1 } };
+	struct pollfd pfd = { .events = POLLIN };
+	struct timespec now;
+	uint64_t ticks;
+	int rfd;
+
+	TEST_ERRNO(timerfd_settime(tfd, TFD_TIMER_CANCEL_ON_SET, &its, NULL),
+		   EINVAL);
+	TEST_ERRNO(timerfd_settime(tfd, 0x4, &its, NULL), EINVAL);
+
+	rfd = CHECK(timerfd_create(CLOCK_REALTIME, TFD_NONBLOCK));
+	pfd.fd = rfd;
+	TEST_SUCC(clock_gettime(CLOCK_REALTIME, &its.it_value));
+	its.it_value.tv_sec += 10;
+	TEST_SUCC(timerfd_settime(
+		rfd, TFD_TIMER_ABSTIME | TFD_TIMER_CANCEL_ON_SET, &its, NULL));
+	TEST_ERRNO(read(rfd, &ticks, sizeof(ticks)), EAGAIN);
+
+	// Setting the real-time clock cancels the timer once.
+	TEST_SUCC(clock_gettime(CLOCK_REALTIME, &now));
+	TEST_SUCC(clock_settime(CLOCK_REALTIME, &now));
+	TEST_RES(poll(&pfd, 1, 0), _ret == 1 && pfd.revents == POLLIN);
+	TEST_ERRNO(read(rfd, &ticks, sizeof(ticks)), ECANCELED);
+	TEST_ERRNO(read(rfd, &ticks, sizeof(ticks)), EAGAIN);
+
+	// A timer armed without the flag is not canceled.
+	TEST_SUCC(timerfd_settime(rfd, TFD_TIMER_ABSTIME, &its, NULL));
+	TEST_SUCC(clock_settime(CLOCK_REALTIME, &now));
+	TEST_ERRNO(read(rfd, &ticks, sizeof(ticks)), EAGAIN);
+
+	TEST_SUCC(close(rfd));
+}
+END_TEST()
+
+FN_TEST(not_timerfd)
+{
+	struct itimerspec its;
+
+	TEST_ERRNO(timerfd_gettime(STDIN_FILENO, &its), EINVAL);
+	TEST_ERRNO(timerfd_gettime(-1, &its), EBADF);
+}
+END_TEST()
+
+FN_SETUP(cleanup)
+{
+	CHECK(close(tfd));
+}
+END_SETUP()
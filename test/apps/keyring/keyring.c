@@ -0,0 +1,324 @@
+// SPDX-License-Identifier: MPL-2.0
+
+#define _GNU_SOURCE
+
+#include "../network/test.h"
+
+#include <linux/keyctl.h>
+#include <stdio.h>
+#include <string.h>
+#include <sys/syscall.h>
+#include <sys/wait.h>
+#include <unistd.h>
+
+typedef int32_t key_serial_t;
+
+static key_serial_t add_key(const char *type, const char *description,
+			    const void *payload, size_t len,
+			    key_serial_t keyring)
+{
+	return syscall(SYS_add_key, type, description, payload, len, keyring);
+}
+
+static key_serial_t request_key(const char *type, const char *description,
+				key_serial_t dest_keyring)
+{
+	return syscall(SYS_request_key, type, description, NULL, dest_keyring);
+}
+
+static long keyctl(int cmd, unsigned long arg2, unsigned long arg3,
+		   unsigned long arg4, unsigned long arg5)
+{
+	return syscall(SYS_keyctl, cmd, arg2, arg3, arg4, arg5);
+}
+
+static long read_key(key_serial_t key, char *buf, size_t len)
+{
+	return keyctl(KEYCTL_READ, key, (unsigned long)buf, len, 0);
+}
+
+static long unlink_key(key_serial_t key, key_serial_t keyring)
+{
+	return keyctl(KEYCTL_UNLINK, key, keyring, 0, 0);
+}
+
+static int wait_status(pid_t pid)
+{
+	int status;
+
+	if (waitpid(pid, &status, 0) != pid)
+		return -1;
+
+	return status;
+}
+
+static int is_exited_with_zero(int status)
+{
+	return status >= 0 && WIFEXITED(status) && WEXITSTATUS(status) == 0;
+}
+
+static char buf[256];
+static char expected[256];
+
+FN_TEST(add_and_read)
+{
+	key_serial_t key;
+
+	key = TEST_RES(add_key("user", "test:add", "secret", 6,
+			       KEY_SPEC_PROCESS_KEYRING),
+		       _ret > 0);
+
+	TEST_RES(read_key(key, buf, sizeof(buf)),
+		 _ret == 6 && memcmp(buf, "secret", 6) == 0);
+	// The payload is not written if the buffer is too small.
+	memset(buf, 0, sizeof(buf));
+	TEST_RES(read_key(key, buf, 3), _ret == 6 && buf[0] == 0);
+	TEST_RES(read_key(key, NULL, 0), _ret == 6);
+
+	// Adding a key of the same description updates the key.
+	TEST_RES(add_key("user", "test:add", "new", 3,
+			 KEY_SPEC_PROCESS_KEYRING),
+		 _ret == key);
+	TEST_RES(read_key(key, buf, sizeof(buf)),
+		 _ret == 3 && memcmp(buf, "new", 3) == 0);
+
+	TEST_SUCC(keyctl(KEYCTL_UPDATE, key, (unsigned long)"updated", 7, 0));
+	TEST_RES(read_key(key, buf, sizeof(buf)),
+		 _ret == 7 && memcmp(buf, "updated", 7) == 0);
+
+	TEST_SUCC(unlink_key(key, KEY_SPEC_PROCESS_KEYRING));
+}
+END_TEST()
+
+FN_TEST(invalid_keys)
+{
+	TEST_ERRNO(add_key("nonexistent", "test", "x", 1,
+			   KEY_SPEC_PROCESS_KEYRING),
+		   ENODEV);
+	TEST_ERRNO(add_key(".internal", "test", "x", 1,
+			   KEY_SPEC_PROCESS_KEYRING),
+		   EPERM);
+	TEST_ERRNO(add_key("user", "", "x", 1, KEY_SPEC_PROCESS_KEYRING),
+		   EINVAL);
+	TEST_ERRNO(add_key("user", "test:empty", NULL, 0,
+			   KEY_SPEC_PROCESS_KEYRING),
+		   EINVAL);
+	TEST_ERRNO(add_key("keyring", "test:ring", "x", 1,
+			   KEY_SPEC_PROCESS_KEYRING),
+		   EINVAL);
+	TEST_ERRNO(add_key("keyring", ".reserved", NULL, 0,
+			   KEY_SPEC_PROCESS_KEYRING),
+		   EPERM);
+	TEST_ERRNO(add_key("logon", "noprefix", "x", 1,
+			   KEY_SPEC_PROCESS_KEYRING),
+		   EINVAL);
+
+	TEST_ERRNO(keyctl(KEYCTL_READ, 0x7fffffff, 0, 0, 0), ENOKEY);
+	TEST_ERRNO(keyctl(-1, 0, 0, 0, 0), EOPNOTSUPP);
+}
+END_TEST()
+
+FN_TEST(describe)
+{
+	key_serial_t key;
+	int len;
+
+	key = TEST_RES(add_key("user", "test:describe", "x", 1,
+			       KEY_SPEC_PROCESS_KEYRING),
+		       _ret > 0);
+
+	len = snprintf(expected, sizeof(expected), "user;%d;%d;3f010000;%s",
+		       getuid(), getgid(), "test:describe");
+	TEST_RES(keyctl(KEYCTL_DESCRIBE, key, (unsigned long)buf, sizeof(buf),
+			0),
+		 _ret == len + 1 && strcmp(buf, expected) == 0);
+	// The description is not written if the buffer is too small.
+	memset(buf, 0, sizeof(buf));
+	TEST_RES(keyctl(KEYCTL_DESCRIBE, key, (unsigned long)buf, len, 0),
+		 _ret == len + 1 && buf[0] == 0);
+
+	TEST_SUCC(keyctl(KEYCTL_SETPERM, key, 0x3f3f0000, 0, 0));
+	len = snprintf(expected, sizeof(expected), "user;%d;%d;3f3f0000;%s",
+		       getuid(), getgid(), "test:describe");
+	TEST_RES(keyctl(KEYCTL_DESCRIBE, key, (unsigned long)buf, sizeof(buf),
+			0),
+		 _ret == len + 1 && strcmp(buf, expected) == 0);
+	TEST_ERRNO(keyctl(KEYCTL_SETPERM, key, 0x40000000, 0, 0), EINVAL);
+
+	TEST_SUCC(keyctl(KEYCTL_INVALIDATE, key, 0, 0, 0));
+}
+END_TEST()
+
+FN_TEST(search)
+{
+	key_serial_t keyring, nested, key;
+
+	keyring = TEST_RES(add_key("keyring", "test:search", NULL, 0,
+				   KEY_SPEC_PROCESS_KEYRING),
+			   _ret > 0);
+	nested = TEST_RES(add_key("keyring", "test:nested", NULL, 0, keyring),
+			  _ret > 0);
+	key = TEST_RES(add_key("user", "test:found", "x", 1, nested),
+		       _ret > 0);
+
+	TEST_RES(keyctl(KEYCTL_SEARCH, keyring, (unsigned long)"user",
+			(unsigned long)"test:found", 0),
+		 _ret == key);
+	TEST_ERRNO(keyctl(KEYCTL_SEARCH, keyring, (unsigned long)"user",
+			  (unsigned long)"test:missing", 0),
+		   ENOKEY);
+	TEST_ERRNO(keyctl(KEYCTL_SEARCH, keyring, (unsigned long)"logon",
+			  (unsigned long)"test:found", 0),
+		   ENOKEY);
+
+	TEST_RES(request_key("user", "test:found", 0), _ret == key);
+	TEST_ERRNO(request_key("user", "test:missing", 0), ENOKEY);
+
+	// The found key can be linked to another keyring.
+	TEST_RES(keyctl(KEYCTL_SEARCH, keyring, (unsigned long)"user",
+			(unsigned long)"test:found", keyring),
+		 _ret == key);
+	TEST_RES(read_key(keyring, buf, sizeof(buf)),
+		 _ret == 8 && ((key_serial_t *)buf)[0] == nested &&
+			 ((key_serial_t *)buf)[1] == key);
+
+	TEST_SUCC(keyctl(KEYCTL_CLEAR, keyring, 0, 0, 0));
+	TEST_RES(read_key(keyring, buf, sizeof(buf)), _ret == 0);
+	TEST_ERRNO(request_key("user", "test:found", 0), ENOKEY);
+
+	TEST_SUCC(unlink_key(keyring, KEY_SPEC_PROCESS_KEYRING));
+}
+END_TEST()
+
+FN_TEST(link_cycle)
+{
+	key_serial_t outer, inner;
+
+	outer = TEST_RES(add_key("keyring", "test:outer", NULL, 0,
+				 KEY_SPEC_PROCESS_KEYRING),
+			 _ret > 0);
+	inner = TEST_RES(add_key("keyring", "test:inner", NULL, 0, outer),
+			 _ret > 0);
+
+	TEST_ERRNO(keyctl(KEYCTL_LINK, outer, inner, 0, 0), EDEADLK);
+	TEST_ERRNO(keyctl(KEYCTL_LINK, outer, outer, 0, 0), EDEADLK);
+	TEST_ERRNO(unlink_key(outer, inner), ENOENT);
+
+	TEST_SUCC(unlink_key(outer, KEY_SPEC_PROCESS_KEYRING));
+}
+END_TEST()
+
+FN_TEST(revoke_and_expire)
+{
+	key_serial_t key;
+
+	key = TEST_RES(add_key("user", "test:revoke", "x", 1,
+			       KEY_SPEC_PROCESS_KEYRING),
+		       _ret > 0);
+	TEST_SUCC(keyctl(KEYCTL_REVOKE, key, 0, 0, 0));
+	TEST_ERRNO(read_key(key, buf, sizeof(buf)), EKEYREVOKED);
+	TEST_ERRNO(keyctl(KEYCTL_UPDATE, key, (unsigned long)"y", 1, 0),
+		   EKEYREVOKED);
+	TEST_SUCC(unlink_key(key, KEY_SPEC_PROCESS_KEYRING));
+
+	key = TEST_RES(add_key("user", "test:expire", "x", 1,
+			       KEY_SPEC_PROCESS_KEYRING),
+		       _ret > 0);
+	TEST_SUCC(keyctl(KEYCTL_SET_TIMEOUT, key, 1, 0, 0));
+	TEST_RES(read_key(key, buf, sizeof(buf)), _ret == 1);
+	sleep(2);
+	TEST_ERRNO(read_key(key, buf, sizeof(buf)), EKEYEXPIRED);
+	TEST_ERRNO(keyctl(KEYCTL_SEARCH, KEY_SPEC_PROCESS_KEYRING,
+			  (unsigned long)"user", (unsigned long)"test:expire", 0),
+		   EKEYEXPIRED);
+	// Unlike searching a keyring, requesting a key ignores the expired keys.
+	TEST_ERRNO(request_key("user", "test:expire", 0), ENOKEY);
+	TEST_SUCC(unlink_key(key, KEY_SPEC_PROCESS_KEYRING));
+}
+END_TEST()
+
+FN_TEST(logon_keys)
+{
+	key_serial_t key;
+
+	key = TEST_RES(add_key("logon", "test:logon", "secret", 6,
+			       KEY_SPEC_PROCESS_KEYRING),
+		       _ret > 0);
+	TEST_ERRNO(read_key(key, buf, sizeof(buf)), EOPNOTSUPP);
+	TEST_RES(request_key("logon", "test:logon", 0), _ret == key);
+	TEST_SUCC(unlink_key(key, KEY_SPEC_PROCESS_KEYRING));
+}
+END_TEST()
+
+FN_TEST(permissions)
+{
+	key_serial_t key;
+
+	key = TEST_RES(add_key("user", "test:perm", "x", 1,
+			       KEY_SPEC_PROCESS_KEYRING),
+		       _ret > 0);
+
+	// A possessed key can be read without the read permission if it can be
+	// found by searching.
+	TEST_SUCC(keyctl(KEYCTL_SETPERM, key, 0x3d010000, 0, 0));
+	TEST_RES(read_key(key, buf, sizeof(buf)), _ret == 1);
+
+	// Otherwise, the key is not possessed and cannot be read.
+	TEST_SUCC(keyctl(KEYCTL_SETPERM, key, 0x35010000, 0, 0));
+	TEST_ERRNO(read_key(key, buf, sizeof(buf)), EACCES);
+	TEST_ERRNO(request_key("user", "test:perm", 0), ENOKEY);
+
+	TEST_SUCC(unlink_key(key, KEY_SPEC_PROCESS_KEYRING));
+}
+END_TEST()
+
+FN_TEST(special_keyrings)
+{
+	key_serial_t thread, process, session;
+	pid_t pid;
+
+	TEST_ERRNO(keyctl(KEYCTL_GET_KEYRING_ID, KEY_SPEC_THREAD_KEYRING, 0, 0,
+			  0),
+		   ENOKEY);
+	thread = TEST_RES(keyctl(KEYCTL_GET_KEYRING_ID,
+				 KEY_SPEC_THREAD_KEYRING, 1, 0, 0),
+			  _ret > 0);
+	TEST_RES(keyctl(KEYCTL_GET_KEYRING_ID, KEY_SPEC_THREAD_KEYRING, 0, 0,
+			0),
+		 _ret == thread);
+
+	process = TEST_RES(keyctl(KEYCTL_GET_KEYRING_ID,
+				  KEY_SPEC_PROCESS_KEYRING, 0, 0, 0),
+			   _ret > 0 && _ret != thread);
+	session = TEST_RES(keyctl(KEYCTL_JOIN_SESSION_KEYRING, 0, 0, 0, 0),
+			   _ret > 0);
+	TEST_RES(keyctl(KEYCTL_GET_KEYRING_ID, KEY_SPEC_SESSION_KEYRING, 0, 0,
+			0),
+		 _ret == session);
+	TEST_RES(keyctl(KEYCTL_GET_KEYRING_ID, KEY_SPEC_USER_KEYRING, 0, 0, 0),
+		 _ret > 0);
+	TEST_ERRNO(keyctl(KEYCTL_GET_KEYRING_ID, -100, 0, 0, 0), EINVAL);
+
+	// The child process inherits the session keyring but not the thread
+	// and process keyrings.
+	pid = TEST_SUCC(fork());
+	if (pid == 0) {
+		if (keyctl(KEYCTL_GET_KEYRING_ID, KEY_SPEC_THREAD_KEYRING, 0,
+			   0, 0) >= 0 ||
+		    errno != ENOKEY)
+			_exit(1);
+		if (keyctl(KEYCTL_GET_KEYRING_ID, KEY_SPEC_PROCESS_KEYRING, 0,
+			   0, 0) >= 0 ||
+		    errno != ENOKEY)
+			_exit(2);
+		if (keyctl(KEYCTL_GET_KEYRING_ID, KEY_SPEC_SESSION_KEYRING, 0,
+			   0, 0) != session)
+			_exit(3);
+		if (keyctl(KEYCTL_GET_KEYRING_ID, process, 0, 0, 0) >= 0 ||
+		    errno != EACCES)
+			_exit(4);
+		_exit(0);
+	}
+	TEST_RES(wait_status(pid), is_exited_with_zero(_ret));
+}
+END_TEST()
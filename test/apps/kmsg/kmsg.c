@@ -0,0 +1,67 @@
+// SPDX-License-Identifier: MPL-2.0
+
+#define _GNU_SOURCE
+
+#include <errno.h>
+#include <fcntl.h>
+#include <string.h>
+#include <sys/klog.h>
+#include <unistd.h>
+
+#include "../network/test.h"
+
+#define SYSLOG_ACTION_READ_ALL 3
+#define SYSLOG_ACTION_SIZE_BUFFER 10
+
+#define MESSAGE "kmsg test message"
+
+static char buf[64 * 1024];
+
+FN_TEST(write_and_read_kmsg)
+{
+	int fd;
+	ssize_t len;
+	int found = 0;
+
+	fd = TEST_SUCC(open("/dev/kmsg", O_RDWR));
+	TEST_RES(write(fd, "<5>" MESSAGE "\n", strlen("<5>" MESSAGE "\n")),
+		 _ret == strlen("<5>" MESSAGE "\n"));
+
+	// Each read returns one record, and the message is the newest one.
+	while (!found) {
+		len = TEST_SUCC(read(fd, buf, sizeof(buf) - 1));
+		buf[len] = '\0';
+		found = strstr(buf, ";" MESSAGE "\n") != NULL;
+	}
+	// The facility of the user programs is `LOG_USER`.
+	TEST_RES(0, strncmp(buf, "13,", 3) == 0);
+
+	TEST_SUCC(close(fd));
+}
+END_TEST()
+
+FN_TEST(small_buffer)
+{
+	int fd;
+
+	fd = TEST_SUCC(open("/dev/kmsg", O_RDONLY));
+	TEST_ERRNO(read(fd, buf, 1), EINVAL);
+	TEST_SUCC(close(fd));
+}
+END_TEST()
+
+FN_TEST(syslog_read_all)
+{
+	int len;
+
+	TEST_RES(klogctl(SYSLOG_ACTION_SIZE_BUFFER, NULL, 0), _ret > 0);
+
+	len = TEST_SUCC(klogctl(SYSLOG_ACTION_READ_ALL, buf, sizeof(buf) - 1));
+	buf[len] = '\0';
+	TEST_RES(0, strstr(buf, "<13>[") != NULL);
+	TEST_RES(0, strstr(buf, "] " MESSAGE "\n") != NULL);
+
+	TEST_ERRNO(klogctl(SYSLOG_ACTION_READ_ALL, NULL, 1), EINVAL);
+	TEST_ERRNO(klogctl(11, buf, sizeof(buf)), EINVAL);
+}
+END_TEST()
@@ -0,0 +1,389 @@
+// SPDX-License-Identifier: MPL-2.0
+
+#define _GNU_SOURCE
+
+#include "../network/test.h"
+
+#include <fcntl.h>
+#include <linux/landlock.h>
+#include <netinet/in.h>
+#include <stdint.h>
+#include <sys/prctl.h>
+#include <sys/socket.h>
+#include <sys/stat.h>
+#include <sys/syscall.h>
+#include <sys/wait.h>
+#include <unistd.h>
+
+#ifndef SYS_landlock_create_ruleset
+#define SYS_landlock_create_ruleset 444
+#define SYS_landlock_add_rule 445
+#define SYS_landlock_restrict_self 446
+#endif
+
+// The definitions of the ABI version 4, which may be missing in old headers.
+#ifndef LANDLOCK_RULE_NET_PORT
+#define LANDLOCK_RULE_NET_PORT 2
+#define LANDLOCK_ACCESS_NET_BIND_TCP (1ULL << 0)
+#define LANDLOCK_ACCESS_NET_CONNECT_TCP (1ULL << 1)
+#endif
+
+struct ruleset_attr {
+	__u64 handled_access_fs;
+	__u64 handled_access_net;
+};
+
+struct net_port_attr {
+	__u64 allowed_access;
+	__u64 port;
+};
+
+#define BASE_DIR "/tmp/landlock"
+#define ALLOWED_DIR BASE_DIR "/allowed"
+#define DENIED_DIR BASE_DIR "/denied"
+
+#define ACCESS_FS                                                     \
+	(LANDLOCK_ACCESS_FS_READ_FILE | LANDLOCK_ACCESS_FS_WRITE_FILE |   \
+	 LANDLOCK_ACCESS_FS_READ_DIR | LANDLOCK_ACCESS_FS_MAKE_REG |      \
+	 LANDLOCK_ACCESS_FS_REMOVE_FILE)
+
+static int create_ruleset(__u64 handled_access_fs, __u64 handled_access_net)
+{
+	struct ruleset_attr attr = {
+		.handled_access_fs = handled_access_fs,
+		.handled_access_net = handled_access_net,
+	};
+
+	return syscall(SYS_landlock_create_ruleset, &attr, sizeof(attr), 0);
+}
+
+static int add_path_rule(int ruleset_fd, const char *path, __u64 access)
+{
+	struct landlock_path_beneath_attr attr = {
+		.allowed_access = access,
+	};
+	int ret;
+
+	attr.parent_fd = open(path, O_PATH | O_CLOEXEC);
+	if (attr.parent_fd < 0)
+		return -1;
+	ret = syscall(SYS_landlock_add_rule, ruleset_fd,
+		      LANDLOCK_RULE_PATH_BENEATH, &attr, 0);
+	close(attr.parent_fd);
+
+	return ret;
+}
+
+static int add_port_rule(int ruleset_fd, __u64 port, __u64 access)
+{
+	struct net_port_attr attr = {
+		.allowed_access = access,
+		.port = port,
+	};
+
+	return syscall(SYS_landlock_add_rule, ruleset_fd,
+		       LANDLOCK_RULE_NET_PORT, &attr, 0);
+}
+
+static int restrict_self(int ruleset_fd)
+{
+	if (prctl(PR_SET_NO_NEW_PRIVS, 1, 0, 0, 0) < 0)
+		return -1;
+	return syscall(SYS_landlock_restrict_self, ruleset_fd, 0);
+}
+
+// Restricts the current process so that only the files in `ALLOWED_DIR`
+// can be accessed with `ACCESS_FS`.
+static int restrict_to_allowed_dir(void)
+{
+	int ruleset_fd, ret;
+
+	ruleset_fd = create_ruleset(ACCESS_FS, 0);
+	if (ruleset_fd < 0)
+		return -1;
+	ret = add_path_rule(ruleset_fd, ALLOWED_DIR, ACCESS_FS);
+	if (ret == 0)
+		ret = restrict_self(ruleset_fd);
+	close(ruleset_fd);
+
+	return ret;
+}
+
+// Returns whether the call failed with the errno.
+static int failed_with(long ret, int err)
+{
+	return ret < 0 && errno == err;
+}
+
+static int open_and_close(const char *path, int flags)
+{
+	int fd;
+
+	fd = open(path, flags, 0644);
+	if (fd < 0)
+		return -1;
+	return close(fd);
+}
+
+static int wait_status(pid_t pid)
+{
+	int status;
+
+	if (waitpid(pid, &status, 0) != pid)
+		return -1;
+
+	return status;
+}
+
+static int is_exited_with_zero(int status)
+{
+	return status >= 0 && WIFEXITED(status) && WEXITSTATUS(status) == 0;
+}
+
+FN_SETUP(create_dirs)
+{
+	CHECK_WITH(mkdir(BASE_DIR, 0755), _ret == 0 || errno == EEXIST);
+	CHECK_WITH(mkdir(ALLOWED_DIR, 0755), _ret == 0 || errno == EEXIST);
+	CHECK_WITH(mkdir(DENIED_DIR, 0755), _ret == 0 || errno == EEXIST);
+	CHECK(open_and_close(ALLOWED_DIR "/file", O_CREAT | O_WRONLY));
+	CHECK(open_and_close(DENIED_DIR "/file", O_CREAT | O_WRONLY));
+}
+END_SETUP()
+
+FN_TEST(abi_version)
+{
+	TEST_RES(syscall(SYS_landlock_create_ruleset, NULL, 0,
+			 LANDLOCK_CREATE_RULESET_VERSION),
+		 _ret >= 4);
+}
+END_TEST()
+
+FN_TEST(invalid_args)
+{
+	struct ruleset_attr attr = { 0 };
+	struct landlock_path_beneath_attr path_attr = {
+		.allowed_access = LANDLOCK_ACCESS_FS_READ_FILE,
+	};
+	int ruleset_fd, dir_fd;
+
+	TEST_ERRNO(create_ruleset(0, 0), ENOMSG);
+	TEST_ERRNO(create_ruleset(1ULL << 63, 0), EINVAL);
+	TEST_ERRNO(syscall(SYS_landlock_create_ruleset, &attr, 4, 0), EINVAL);
+
+	ruleset_fd = TEST_SUCC(create_ruleset(LANDLOCK_ACCESS_FS_READ_FILE, 0));
+	dir_fd = TEST_SUCC(open(ALLOWED_DIR, O_RDONLY | O_DIRECTORY));
+
+	// The file descriptor must refer to a ruleset.
+	path_attr.parent_fd = dir_fd;
+	TEST_ERRNO(syscall(SYS_landlock_add_rule, dir_fd,
+			   LANDLOCK_RULE_PATH_BENEATH, &path_attr, 0),
+		   EBADFD);
+
+	// The access rights must be handled by the ruleset.
+	TEST_ERRNO(add_path_rule(ruleset_fd, ALLOWED_DIR,
+				 LANDLOCK_ACCESS_FS_WRITE_FILE),
+		   EINVAL);
+	TEST_ERRNO(add_path_rule(ruleset_fd, ALLOWED_DIR, 0), ENOMSG);
+
+	// The rights of directories cannot be granted to files.
+	TEST_SUCC(close(ruleset_fd));
+	ruleset_fd = TEST_SUCC(create_ruleset(LANDLOCK_ACCESS_FS_READ_DIR, 0));
+	TEST_ERRNO(add_path_rule(ruleset_fd, ALLOWED_DIR "/file",
+				 LANDLOCK_ACCESS_FS_READ_DIR),
+		   EINVAL);
+
+	TEST_SUCC(close(ruleset_fd));
+	TEST_SUCC(close(dir_fd));
+}
+END_TEST()
+
+FN_TEST(fs_access)
+{
+	pid_t pid;
+
+	pid = TEST_SUCC(fork());
+	if (pid == 0) {
+		if (restrict_to_allowed_dir() < 0)
+			_exit(1);
+
+		// The files beneath the allowed directory can be accessed.
+		if (open_and_close(ALLOWED_DIR "/file", O_RDWR) < 0)
+			_exit(2);
+		if (open_and_close(ALLOWED_DIR, O_RDONLY | O_DIRECTORY) < 0)
+			_exit(3);
+		if (open_and_close(ALLOWED_DIR "/new", O_CREAT | O_WRONLY) < 0)
+			_exit(4);
+		if (unlink(ALLOWED_DIR "/new") < 0)
+			_exit(5);
+
+		// Other files cannot be accessed.
+		if (!failed_with(open_and_close(DENIED_DIR "/file", O_RDONLY),
+				 EACCES))
+			_exit(6);
+		if (!failed_with(open_and_close(DENIED_DIR,
+						O_RDONLY | O_DIRECTORY),
+				 EACCES))
+			_exit(7);
+		if (!failed_with(open_and_close(DENIED_DIR "/new",
+						O_CREAT | O_WRONLY),
+				 EACCES))
+			_exit(8);
+		if (!failed_with(unlink(DENIED_DIR "/file"), EACCES))
+			_exit(9);
+
+		// The accesses that are not handled are allowed.
+		if (open_and_close(DENIED_DIR "/file", O_PATH) < 0)
+			_exit(10);
+		if (mkdir(DENIED_DIR "/dir", 0755) < 0 ||
+		    rmdir(DENIED_DIR "/dir") < 0)
+			_exit(11);
+
+		// The domain is inherited by the child processes.
+		pid = fork();
+		if (pid == 0) {
+			if (!failed_with(open_and_close(DENIED_DIR "/file",
+							O_RDONLY),
+					 EACCES))
+				_exit(1);
+			_exit(0);
+		}
+		_exit(is_exited_with_zero(wait_status(pid)) ? 0 : 12);
+	}
+	TEST_RES(wait_status(pid), is_exited_with_zero(_ret));
+}
+END_TEST()
+
+FN_TEST(refer)
+{
+	pid_t pid;
+
+	pid = TEST_SUCC(fork());
+	if (pid == 0) {
+		if (restrict_to_allowed_dir() < 0)
+			_exit(1);
+
+		// Files can be renamed in the same directory.
+		if (rename(ALLOWED_DIR "/file", ALLOWED_DIR "/renamed") < 0 ||
+		    rename(ALLOWED_DIR "/renamed", ALLOWED_DIR "/file") < 0)
+			_exit(2);
+
+		// Files cannot be moved to other directories without
+		// `LANDLOCK_ACCESS_FS_REFER`.
+		if (mkdir(ALLOWED_DIR "/dir", 0755) < 0)
+			_exit(3);
+		if (!failed_with(rename(ALLOWED_DIR "/file",
+					ALLOWED_DIR "/dir/file"),
+				 EXDEV))
+			_exit(4);
+		if (!failed_with(link(ALLOWED_DIR "/file",
+				      ALLOWED_DIR "/dir/file"),
+				 EXDEV))
+			_exit(5);
+		_exit(0);
+	}
+	TEST_RES(wait_status(pid), is_exited_with_zero(_ret));
+	TEST_SUCC(rmdir(ALLOWED_DIR "/dir"));
+}
+END_TEST()
+
+FN_TEST(layers)
+{
+	pid_t pid;
+
+	pid = TEST_SUCC(fork());
+	if (pid == 0) {
+		int ruleset_fd;
+
+		if (restrict_to_allowed_dir() < 0)
+			_exit(1);
+
+		// Adding rules to an enforced ruleset does not affect the
+		// domain.
+		ruleset_fd = create_ruleset(LANDLOCK_ACCESS_FS_WRITE_FILE, 0);
+		if (ruleset_fd < 0 || restrict_self(ruleset_fd) < 0)
+			_exit(2);
+		if (add_path_rule(ruleset_fd, ALLOWED_DIR,
+				  LANDLOCK_ACCESS_FS_WRITE_FILE) < 0)
+			_exit(3);
+
+		// The new layer further restricts the accesses.
+		if (open_and_close(ALLOWED_DIR "/file", O_RDONLY) < 0)
+			_exit(4);
+		if (!failed_with(open_and_close(ALLOWED_DIR "/file", O_WRONLY),
+				 EACCES))
+			_exit(5);
+
+		// The rules of the new layer cannot relax the old layers.
+		ruleset_fd = create_ruleset(LANDLOCK_ACCESS_FS_READ_FILE, 0);
+		if (ruleset_fd < 0 ||
+		    add_path_rule(ruleset_fd, DENIED_DIR,
+				  LANDLOCK_ACCESS_FS_READ_FILE) < 0 ||
+		    restrict_self(ruleset_fd) < 0)
+			_exit(6);
+		if (!failed_with(open_and_close(DENIED_DIR "/file", O_RDONLY),
+				 EACCES))
+			_exit(7);
+		_exit(0);
+	}
+	TEST_RES(wait_status(pid), is_exited_with_zero(_ret));
+}
+END_TEST()
+
+FN_TEST(net_access)
+{
+	pid_t pid;
+
+	pid = TEST_SUCC(fork());
+	if (pid == 0) {
+		struct sockaddr_in addr = {
+			.sin_family = AF_INET,
+			.sin_addr = { htonl(INADDR_LOOPBACK) },
+		};
+		int ruleset_fd, sk;
+
+		ruleset_fd = create_ruleset(0, LANDLOCK_ACCESS_NET_BIND_TCP);
+		if (ruleset_fd < 0)
+			_exit(1);
+		if (add_port_rule(ruleset_fd, 8080,
+				  LANDLOCK_ACCESS_NET_BIND_TCP) < 0)
+			_exit(2);
+		if (!failed_with(add_port_rule(ruleset_fd, 65536,
+					       LANDLOCK_ACCESS_NET_BIND_TCP),
+				 EINVAL))
+			_exit(3);
+		if (restrict_self(ruleset_fd) < 0)
+			_exit(4);
+
+		sk = socket(AF_INET, SOCK_STREAM, 0);
+		if (sk < 0)
+			_exit(5);
+		addr.sin_port = htons(8081);
+		if (!failed_with(bind(sk, (struct sockaddr *)&addr,
+				      sizeof(addr)),
+				 EACCES))
+			_exit(6);
+		addr.sin_port = htons(8080);
+		if (bind(sk, (struct sockaddr *)&addr, sizeof(addr)) < 0)
+			_exit(7);
+
+		// Only TCP sockets are restricted.
+		sk = socket(AF_INET, SOCK_DGRAM, 0);
+		addr.sin_port = htons(8081);
+		if (sk < 0 ||
+		    bind(sk, (struct sockaddr *)&addr, sizeof(addr)) < 0)
+			_exit(8);
+		_exit(0);
+	}
+	TEST_RES(wait_status(pid), is_exited_with_zero(_ret));
+}
+END_TEST()
+
+FN_SETUP(cleanup)
+{
+	CHECK(unlink(ALLOWED_DIR "/file"));
+	CHECK(unlink(DENIED_DIR "/file"));
+	CHECK(rmdir(ALLOWED_DIR));
+	CHECK(rmdir(DENIED_DIR));
+	CHECK(rmdir(BASE_DIR));
+}
+END_SETUP()
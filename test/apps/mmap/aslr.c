@@ -0,0 +1,113 @@
+// SPDX-License-Identifier: MPL-2.0
+
+#define _GNU_SOURCE
+
+#include <stdio.h>
+#include <stdlib.h>
+#include <sys/mman.h>
+#include <sys/personality.h>
+#include <sys/wait.h>
+#include <unistd.h>
+
+#include "../network/test.h"
+
+#define PAGE_SIZE 4096
+#define PERSONALITY_QUERY 0xffffffff
+#define REPORT_FD_ENV "ASLR_REPORT_FD"
+
+struct layout {
+	unsigned long stack;
+	unsigned long mmap;
+	unsigned long brk;
+};
+
+// When the program is executed by `get_layout`, it reports its layout and
+// exits before running any tests.
+__attribute__((constructor(101))) static void report_layout(void)
+{
+	const char *report_fd = getenv(REPORT_FD_ENV);
+	struct layout layout;
+	int local;
+
+	if (report_fd == NULL)
+		return;
+
+	layout.stack = (unsigned long)&local;
+	layout.mmap = (unsigned long)mmap(NULL, PAGE_SIZE, PROT_READ,
+					  MAP_PRIVATE | MAP_ANONYMOUS, -1, 0);
+	layout.brk = (unsigned long)sbrk(0);
+	if (write(atoi(report_fd), &layout, sizeof(layout)) != sizeof(layout))
+		_exit(EXIT_FAILURE);
+	_exit(EXIT_SUCCESS);
+}
+
+static int get_layout(unsigned long persona, struct layout *layout)
+{
+	char report_fd[16];
+	int fds[2], status;
+	pid_t pid;
+
+	if (pipe(fds) < 0)
+		return -1;
+
+	pid = fork();
+	if (pid < 0)
+		return -1;
+	if (pid == 0) {
+		close(fds[0]);
+		snprintf(report_fd, sizeof(report_fd), "%d", fds[1]);
+		setenv(REPORT_FD_ENV, report_fd, 1);
+		if (personality(persona) < 0)
+			_exit(EXIT_FAILURE);
+		execl("/proc/self/exe", "aslr", NULL);
+		_exit(EXIT_FAILURE);
+	}
+
+	close(fds[1]);
+	if (read(fds[0], layout, sizeof(*layout)) != sizeof(*layout)) {
+		close(fds[0]);
+		waitpid(pid, &status, 0);
+		return -1;
+	}
+	close(fds[0]);
+
+	if (waitpid(pid, &status, 0) != pid || !WIFEXITED(status) ||
+	    WEXITSTATUS(status) != EXIT_SUCCESS)
+		return -1;
+	return 0;
+}
+
+FN_TEST(personality)
+{
+	int old;
+
+	old = TEST_SUCC(personality(PERSONALITY_QUERY));
+	TEST_RES(personality(old | ADDR_NO_RANDOMIZE), _ret == old);
+	TEST_RES(personality(PERSONALITY_QUERY),
+		 _ret == (old | ADDR_NO_RANDOMIZE));
+	TEST_RES(personality(old), _ret == (old | ADDR_NO_RANDOMIZE));
+	TEST_RES(personality(PERSONALITY_QUERY), _ret == old);
+}
+END_TEST()
+
+FN_TEST(randomized)
+{
+	struct layout first, second;
+
+	TEST_SUCC(get_layout(0, &first));
+	TEST_RES(get_layout(0, &second),
+		 first.stack != second.stack && first.mmap != second.mmap &&
+			 first.brk != second.brk);
+}
+END_TEST()
+
+FN_TEST(not_randomized)
+{
+	struct layout first, second;
+
+	TEST_SUCC(get_layout(ADDR_NO_RANDOMIZE, &first));
+	TEST_RES(get_layout(ADDR_NO_RANDOMIZE, &second),
+		 first.stack == second.stack && first.mmap == second.mmap &&
+			 first.brk == second.brk);
+}
+END_TEST()
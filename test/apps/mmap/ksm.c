@@ -0,0 +1,140 @@
+// SPDX-License-Identifier: MPL-2.0
+
+#define _GNU_SOURCE
+
+#include <fcntl.h>
+#include <stdio.h>
+#include <stdlib.h>
+#include <string.h>
+#include <sys/mman.h>
+#include <unistd.h>
+
+#include "../network/test.h"
+
+#define KSM_DIR "/proc/sys/vm/ksm/"
+
+#define PAGE_SIZE 4096
+#define NR_PAGES 4
+
+static void write_knob(const char *name, const char *value)
+{
+	char path[64];
+	int fd;
+
+	snprintf(path, sizeof(path), KSM_DIR "%s", name);
+	fd = CHECK(open(path, O_WRONLY));
+	CHECK(write(fd, value, strlen(value)));
+	CHECK(close(fd));
+}
+
+static long read_knob(const char *name)
+{
+	char path[64], buf[32] = { 0 };
+	int fd;
+
+	snprintf(path, sizeof(path), KSM_DIR "%s", name);
+	fd = CHECK(open(path, O_RDONLY));
+	CHECK(read(fd, buf, sizeof(buf) - 1));
+	CHECK(close(fd));
+
+	return atol(buf);
+}
+
+static char *map_pages(void)
+{
+	char *addr;
+
+	addr = mmap(NULL, PAGE_SIZE * NR_PAGES, PROT_READ | PROT_WRITE,
+		    MAP_PRIVATE | MAP_ANONYMOUS, -1, 0);
+	CHECK(addr == MAP_FAILED ? -1 : 0);
+
+	return addr;
+}
+
+static int wait_for_sharing(void)
+{
+	int i;
+
+	for (i = 0; i < 500; ++i) {
+		if (read_knob("pages_sharing") > 0)
+			return 0;
+		usleep(10 * 1000);
+	}
+
+	return -1;
+}
+
+FN_SETUP(start)
+{
+	write_knob("pages_to_scan", "1000");
+	write_knob("sleep_millisecs", "10");
+	write_knob("run", "1");
+}
+END_SETUP()
+
+FN_TEST(invalid_knobs)
+{
+	int fd;
+
+	fd = TEST_SUCC(open(KSM_DIR "run", O_WRONLY));
+	TEST_ERRNO(write(fd, "3", 1), EINVAL);
+	TEST_ERRNO(write(fd, "abc", 3), EINVAL);
+	TEST_SUCC(close(fd));
+}
+END_TEST()
+
+FN_TEST(merge_and_break)
+{
+	char *addr1 = map_pages();
+	char *addr2 = map_pages();
+
+	memset(addr1, 'k', PAGE_SIZE * NR_PAGES);
+	memset(addr2, 'k', PAGE_SIZE * NR_PAGES);
+
+	TEST_SUCC(madvise(addr1, PAGE_SIZE * NR_PAGES, MADV_MERGEABLE));
+	TEST_SUCC(madvise(addr2, PAGE_SIZE * NR_PAGES, MADV_MERGEABLE));
+	TEST_SUCC(wait_for_sharing());
+
+	// Writing to a merged page gives the writer a private copy.
+	addr1[0] = 'x';
+	TEST_RES(addr1[0], _ret == 'x');
+	TEST_RES(addr2[0], _ret == 'k');
+	TEST_RES(addr1[PAGE_SIZE], _ret == 'k');
+
+	TEST_SUCC(madvise(addr1, PAGE_SIZE * NR_PAGES, MADV_UNMERGEABLE));
+	TEST_RES(addr1[PAGE_SIZE], _ret == 'k');
+
+	TEST_SUCC(munmap(addr1, PAGE_SIZE * NR_PAGES));
+	TEST_SUCC(munmap(addr2, PAGE_SIZE * NR_PAGES));
+}
+END_TEST()
+
+FN_TEST(unmerge_all)
+{
+	char *addr1 = map_pages();
+	char *addr2 = map_pages();
+
+	memset(addr1, 'u', PAGE_SIZE * NR_PAGES);
+	memset(addr2, 'u', PAGE_SIZE * NR_PAGES);
+
+	TEST_SUCC(madvise(addr1, PAGE_SIZE * NR_PAGES, MADV_MERGEABLE));
+	TEST_SUCC(madvise(addr2, PAGE_SIZE * NR_PAGES, MADV_MERGEABLE));
+	TEST_SUCC(wait_for_sharing());
+
+	write_knob("run", "2");
+	TEST_RES(read_knob("pages_sharing"), _ret == 0);
+	TEST_RES(read_knob("run"), _ret == 2);
+
+	addr2[0] = 'y';
+	TEST_RES(addr1[0], _ret == 'u');
+
+	TEST_SUCC(munmap(addr1, PAGE_SIZE * NR_PAGES));
+	TEST_SUCC(munmap(addr2, PAGE_SIZE * NR_PAGES));
+}
+END_TEST()
+
+FN_SETUP(stop)
+{
+	write_knob("run", "0");
+}
+END_SETUP()
@@ -0,0 +1,150 @@
+// SPDX-License-Identifier: MPL-2.0
+
+#define _GNU_SOURCE
+
+#include <fcntl.h>
+#include <string.h>
+#include <sys/mman.h>
+#include <unistd.h>
+
+#include "../network/test.h"
+
+#define FILE_NAME "/tmp/madvise.txt"
+
+#define PAGE_SIZE 4096
+#define NR_PAGES 4
+
+static int fd;
+
+static char *map_pages(int flags, int map_fd)
+{
+	char *addr;
+
+	addr = mmap(NULL, PAGE_SIZE * NR_PAGES, PROT_READ | PROT_WRITE, flags,
+		    map_fd, 0);
+	CHECK(addr == MAP_FAILED ? -1 : 0);
+
+	return addr;
+}
+
+FN_SETUP(file)
+{
+	char buf[PAGE_SIZE];
+	int i;
+
+	fd = CHECK(open(FILE_NAME, O_RDWR | O_CREAT | O_TRUNC, 0666));
+	CHECK(unlink(FILE_NAME));
+
+	memset(buf, 'f', PAGE_SIZE);
+	for (i = 0; i < NR_PAGES; ++i)
+		CHECK(write(fd, buf, PAGE_SIZE));
+}
+END_SETUP()
+
+FN_TEST(invalid_args)
+{
+	char *addr = map_pages(MAP_PRIVATE | MAP_ANONYMOUS, -1);
+
+	TEST_ERRNO(madvise(addr + 1, PAGE_SIZE, MADV_DONTNEED), EINVAL);
+	TEST_ERRNO(madvise(addr, PAGE_SIZE, 0x12345), EINVAL);
+
+	// The range contains unmapped pages.
+	TEST_SUCC(munmap(addr + PAGE_SIZE * (NR_PAGES - 1), PAGE_SIZE));
+	TEST_ERRNO(madvise(addr, PAGE_SIZE * NR_PAGES, MADV_DONTNEED), ENOMEM);
+	TEST_ERRNO(madvise(addr, PAGE_SIZE * NR_PAGES, MADV_WILLNEED), ENOMEM);
+	TEST_ERRNO(madvise(addr, PAGE_SIZE * NR_PAGES, MADV_NORMAL), ENOMEM);
+
+	TEST_SUCC(madvise(addr, 0, MADV_DONTNEED));
+
+	TEST_SUCC(munmap(addr, PAGE_SIZE * (NR_PAGES - 1)));
+}
+END_TEST()
+
+FN_TEST(dontneed_anonymous)
+{
+	char *addr = map_pages(MAP_PRIVATE | MAP_ANONYMOUS, -1);
+
+	memset(addr, 'a', PAGE_SIZE * NR_PAGES);
+
+	// The discarded pages are read as zeros.
+	TEST_SUCC(madvise(addr + PAGE_SIZE, PAGE_SIZE * 2, MADV_DONTNEED));
+	TEST_RES(addr[0] + addr[PAGE_SIZE] + addr[PAGE_SIZE * 2] +
+			 addr[PAGE_SIZE * 3],
+		 _ret == 'a' * 2);
+
+	addr[PAGE_SIZE] = 'b';
+	TEST_RES(addr[PAGE_SIZE], _ret == 'b');
+
+	TEST_SUCC(munmap(addr, PAGE_SIZE * NR_PAGES));
+}
+END_TEST()
+
+FN_TEST(dontneed_file)
+{
+	char *private = map_pages(MAP_PRIVATE, fd);
+	char *shared = map_pages(MAP_SHARED, fd);
+
+	// The private copies are discarded, so the file contents are read.
+	private[0] = 'p';
+	TEST_SUCC(madvise(private, PAGE_SIZE, MADV_DONTNEED));
+	TEST_RES(private[0], _ret == 'f');
+
+	// The shared pages are kept in the file.
+	shared[0] = 's';
+	TEST_SUCC(madvise(shared, PAGE_SIZE, MADV_DONTNEED));
+	TEST_RES(shared[0], _ret == 's');
+	TEST_RES(private[0], _ret == 's');
+
+	TEST_SUCC(munmap(private, PAGE_SIZE * NR_PAGES));
+	TEST_SUCC(munmap(shared, PAGE_SIZE * NR_PAGES));
+}
+END_TEST()
+
+FN_TEST(free)
+{
+	char *anonymous = map_pages(MAP_PRIVATE | MAP_ANONYMOUS, -1);
+	char *file = map_pages(MAP_PRIVATE, fd);
+
+	memset(anonymous, 'a', PAGE_SIZE * NR_PAGES);
+
+	// The freed pages are read as either the original contents or zeros.
+	TEST_SUCC(madvise(anonymous, PAGE_SIZE * NR_PAGES, MADV_FREE));
+	TEST_RES(anonymous[0], _ret == 'a' || _ret == 0);
+
+	anonymous[0] = 'b';
+	TEST_RES(anonymous[0], _ret == 'b');
+
+	// Only anonymous pages can be freed.
+	TEST_ERRNO(madvise(file, PAGE_SIZE, MADV_FREE), EINVAL);
+
+	TEST_SUCC(munmap(anonymous, PAGE_SIZE * NR_PAGES));
+	TEST_SUCC(munmap(file, PAGE_SIZE * NR_PAGES));
+}
+END_TEST()
+
+FN_TEST(hints)
+{
+	char *anonymous = map_pages(MAP_PRIVATE | MAP_ANONYMOUS, -1);
+	char *file = map_pages(MAP_SHARED, fd);
+
+	TEST_SUCC(madvise(file, PAGE_SIZE * NR_PAGES, MADV_WILLNEED));
+	TEST_RES(file[PAGE_SIZE * 2], _ret == 'f');
+	TEST_SUCC(madvise(anonymous, PAGE_SIZE * NR_PAGES, MADV_WILLNEED));
+
+	TEST_SUCC(madvise(anonymous, PAGE_SIZE * NR_PAGES, MADV_SEQUENTIAL));
+	TEST_SUCC(madvise(anonymous, PAGE_SIZE * NR_PAGES, MADV_RANDOM));
+	TEST_SUCC(madvise(anonymous, PAGE_SIZE * NR_PAGES, MADV_NORMAL));
+
+	TEST_SUCC(madvise(anonymous, PAGE_SIZE * NR_PAGES, MADV_HUGEPAGE));
+	TEST_SUCC(madvise(anonymous, PAGE_SIZE * NR_PAGES, MADV_NOHUGEPAGE));
+
+	TEST_SUCC(munmap(anonymous, PAGE_SIZE * NR_PAGES));
+	TEST_SUCC(munmap(file, PAGE_SIZE * NR_PAGES));
+}
+END_TEST()
+
+FN_SETUP(cleanup)
+{
+	CHECK(close(fd));
+}
+END_SETUP()
@@ -0,0 +1,170 @@
+// SPDX-License-Identifier: MPL-2.0
+
+#define _GNU_SOURCE
+
+#include <string.h>
+#include <sys/mman.h>
+#include <sys/syscall.h>
+#include <unistd.h>
+
+#include "../network/test.h"
+
+#define MPOL_DEFAULT 0
+#define MPOL_PREFERRED 1
+#define MPOL_BIND 2
+#define MPOL_INTERLEAVE 3
+#define MPOL_LOCAL 4
+
+#define MPOL_F_STATIC_NODES (1 << 15)
+#define MPOL_F_RELATIVE_NODES (1 << 14)
+
+#define MPOL_F_NODE (1 << 0)
+#define MPOL_F_ADDR (1 << 1)
+#define MPOL_F_MEMS_ALLOWED (1 << 2)
+
+#define PAGE_SIZE 4096
+#define NR_PAGES 4
+#define MAX_NODE 64
+
+static long set_mempolicy(int mode, unsigned long *nodemask,
+			  unsigned long maxnode)
+{
+	return syscall(SYS_set_mempolicy, mode, nodemask, maxnode);
+}
+
+static long get_mempolicy(int *mode, unsigned long *nodemask,
+			  unsigned long maxnode, void *addr,
+			  unsigned long flags)
+{
+	return syscall(SYS_get_mempolicy, mode, nodemask, maxnode, addr,
+		       flags);
+}
+
+static long mbind(void *addr, unsigned long len, int mode,
+		  unsigned long *nodemask, unsigned long maxnode,
+		  unsigned int flags)
+{
+	return syscall(SYS_mbind, addr, len, mode, nodemask, maxnode, flags);
+}
+
+FN_TEST(set_and_get)
+{
+	unsigned long nodes;
+	int mode;
+
+	nodes = 1;
+	TEST_SUCC(set_mempolicy(MPOL_BIND, &nodes, MAX_NODE + 1));
+	nodes = 0;
+	TEST_RES(get_mempolicy(&mode, &nodes, MAX_NODE + 1, NULL, 0),
+		 mode == MPOL_BIND && nodes == 1);
+
+	nodes = 1;
+	TEST_SUCC(set_mempolicy(MPOL_INTERLEAVE, &nodes, MAX_NODE + 1));
+	TEST_RES(get_mempolicy(&mode, NULL, 0, NULL, MPOL_F_NODE), mode == 0);
+
+	nodes = 1;
+	TEST_SUCC(set_mempolicy(MPOL_PREFERRED | MPOL_F_STATIC_NODES, &nodes,
+				MAX_NODE + 1));
+	TEST_RES(get_mempolicy(&mode, NULL, 0, NULL, 0),
+		 mode == (MPOL_PREFERRED | MPOL_F_STATIC_NODES));
+
+	TEST_SUCC(set_mempolicy(MPOL_DEFAULT, NULL, 0));
+	nodes = 1;
+	TEST_RES(get_mempolicy(&mode, &nodes, MAX_NODE + 1, NULL, 0),
+		 mode == MPOL_DEFAULT && nodes == 0);
+}
+END_TEST()
+
+FN_TEST(invalid_args)
+{
+	unsigned long nodes;
+	int mode;
+
+	// The node set is empty.
+	nodes = 0;
+	TEST_ERRNO(set_mempolicy(MPOL_BIND, &nodes, MAX_NODE + 1), EINVAL);
+	TEST_ERRNO(set_mempolicy(MPOL_BIND, NULL, 0), EINVAL);
+
+	// The node set is not accepted.
+	nodes = 1;
+	TEST_ERRNO(set_mempolicy(MPOL_DEFAULT, &nodes, MAX_NODE + 1), EINVAL);
+	TEST_ERRNO(set_mempolicy(MPOL_LOCAL, &nodes, MAX_NODE + 1), EINVAL);
+
+	// The mode or the flags are invalid.
+	TEST_ERRNO(set_mempolicy(0x123, &nodes, MAX_NODE + 1), EINVAL);
+	TEST_ERRNO(set_mempolicy(MPOL_BIND | MPOL_F_STATIC_NODES |
+					 MPOL_F_RELATIVE_NODES,
+				 &nodes, MAX_NODE + 1),
+		   EINVAL);
+	TEST_ERRNO(get_mempolicy(&mode, NULL, 0, NULL, 0x100), EINVAL);
+	TEST_ERRNO(get_mempolicy(&mode, NULL, 0, NULL,
+				 MPOL_F_MEMS_ALLOWED | MPOL_F_NODE),
+		   EINVAL);
+
+	// The next interleave node is queried without the interleave mode.
+	TEST_ERRNO(get_mempolicy(&mode, NULL, 0, NULL, MPOL_F_NODE), EINVAL);
+
+	// The address is specified without `MPOL_F_ADDR`.
+	TEST_ERRNO(get_mempolicy(&mode, NULL, 0, &mode, 0), EINVAL);
+}
+END_TEST()
+
+FN_TEST(mems_allowed)
+{
+	unsigned long nodes = 0;
+	int mode;
+
+	TEST_RES(get_mempolicy(&mode, &nodes, MAX_NODE + 1, NULL,
+			       MPOL_F_MEMS_ALLOWED),
+		 (nodes & 1) == 1);
+}
+END_TEST()
+
+FN_TEST(mbind)
+{
+	unsigned long nodes;
+	char *addr;
+	int mode;
+
+	addr = mmap(NULL, PAGE_SIZE * NR_PAGES, PROT_READ | PROT_WRITE,
+		    MAP_PRIVATE | MAP_ANONYMOUS, -1, 0);
+	CHECK(addr == MAP_FAILED ? -1 : 0);
+
+	nodes = 1;
+	TEST_SUCC(mbind(addr + PAGE_SIZE, PAGE_SIZE, MPOL_BIND, &nodes,
+			MAX_NODE + 1, 0));
+	TEST_RES(get_mempolicy(&mode, NULL, 0, addr + PAGE_SIZE, MPOL_F_ADDR),
+		 mode == MPOL_BIND);
+	TEST_RES(get_mempolicy(&mode, NULL, 0, addr, MPOL_F_ADDR),
+		 mode == MPOL_DEFAULT);
+
+	// The pages are allocated from the bound node.
+	memset(addr, 'a', PAGE_SIZE * NR_PAGES);
+	TEST_RES(get_mempolicy(&mode, NULL, 0, addr + PAGE_SIZE,
+			       MPOL_F_ADDR | MPOL_F_NODE),
+		 mode == 0);
+
+	TEST_SUCC(mbind(addr + PAGE_SIZE, PAGE_SIZE, MPOL_DEFAULT, NULL, 0, 0));
+	TEST_RES(get_mempolicy(&mode, NULL, 0, addr + PAGE_SIZE, MPOL_F_ADDR),
+		 mode == MPOL_DEFAULT);
+
+	TEST_ERRNO(mbind(addr + 1, PAGE_SIZE, MPOL_BIND, &nodes, MAX_NODE + 1,
+			 0),
+		   EINVAL);
+	TEST_ERRNO(mbind(addr, PAGE_SIZE, MPOL_BIND, &nodes, MAX_NODE + 1,
+			 0x100),
+		   EINVAL);
+	TEST_SUCC(mbind(addr, 0, MPOL_BIND, &nodes, MAX_NODE + 1, 0));
+
+	// The range contains unmapped pages.
+	TEST_SUCC(munmap(addr + PAGE_SIZE * 2, PAGE_SIZE));
+	TEST_ERRNO(mbind(addr, PAGE_SIZE * NR_PAGES, MPOL_BIND, &nodes,
+			 MAX_NODE + 1, 0),
+		   EFAULT);
+	TEST_ERRNO(get_mempolicy(&mode, NULL, 0, addr + PAGE_SIZE * 2,
+				 MPOL_F_ADDR),
+		   EFAULT);
+
+	TEST_SUCC(munmap(addr, PAGE_SIZE * NR_PAGES));
+}
+END_TEST()
@@ -0,0 +1,123 @@
+// SPDX-License-Identifier: MPL-2.0
+
+#define _GNU_SOURCE
+
+#include <stdio.h>
+#include <stdlib.h>
+#include <string.h>
+#include <sys/mman.h>
+#include <sys/syscall.h>
+#include <unistd.h>
+
+#include "../network/test.h"
+
+#define PAGE_SIZE 4096
+#define NR_PAGES 4
+
+static char *map_pages(void)
+{
+	char *addr;
+
+	addr = mmap(NULL, PAGE_SIZE * NR_PAGES, PROT_READ | PROT_WRITE,
+		    MAP_PRIVATE | MAP_ANONYMOUS, -1, 0);
+	CHECK(addr == MAP_FAILED ? -1 : 0);
+
+	return addr;
+}
+
+static long locked_kb(void)
+{
+	char line[128];
+	long size = -1;
+	FILE *file;
+
+	file = fopen("/proc/self/status", "r");
+	if (file == NULL)
+		return -1;
+	while (fgets(line, sizeof(line), file))
+		if (sscanf(line, "VmLck: %ld kB", &size) == 1)
+			break;
+	fclose(file);
+
+	return size;
+}
+
+FN_TEST(invalid_args)
+{
+	char *addr = map_pages();
+
+	TEST_ERRNO(syscall(SYS_mlock2, addr, PAGE_SIZE, 0x100), EINVAL);
+	TEST_ERRNO(mlockall(0), EINVAL);
+	TEST_ERRNO(mlockall(MCL_ONFAULT), EINVAL);
+
+	// The range contains unmapped pages.
+	TEST_SUCC(munmap(addr + PAGE_SIZE * (NR_PAGES - 1), PAGE_SIZE));
+	TEST_ERRNO(mlock(addr, PAGE_SIZE * NR_PAGES), ENOMEM);
+	TEST_RES(locked_kb(), _ret == 0);
+
+	TEST_SUCC(mlock(addr, 0));
+
+	TEST_SUCC(munmap(addr, PAGE_SIZE * (NR_PAGES - 1)));
+}
+END_TEST()
+
+FN_TEST(mlock_munlock)
+{
+	char *addr = map_pages();
+
+	// The start address does not need to be page aligned.
+	TEST_SUCC(mlock(addr + 1, PAGE_SIZE));
+	TEST_RES(locked_kb(), _ret == PAGE_SIZE * 2 / 1024);
+
+	// The locked pages cannot be discarded.
+	TEST_ERRNO(madvise(addr, PAGE_SIZE, MADV_DONTNEED), EINVAL);
+	TEST_SUCC(madvise(addr + PAGE_SIZE * 2, PAGE_SIZE, MADV_DONTNEED));
+
+	TEST_SUCC(syscall(SYS_mlock2, addr, PAGE_SIZE * NR_PAGES,
+			  MLOCK_ONFAULT));
+	TEST_RES(locked_kb(), _ret == PAGE_SIZE * NR_PAGES / 1024);
+
+	TEST_SUCC(munlock(addr, PAGE_SIZE));
+	TEST_RES(locked_kb(), _ret == PAGE_SIZE * (NR_PAGES - 1) / 1024);
+	TEST_SUCC(madvise(addr, PAGE_SIZE, MADV_DONTNEED));
+
+	TEST_SUCC(munmap(addr, PAGE_SIZE * NR_PAGES));
+	TEST_RES(locked_kb(), _ret == 0);
+}
+END_TEST()
+
+FN_TEST(mlockall_munlockall)
+{
+	char *addr1 = map_pages();
+	char *addr2;
+
+	TEST_SUCC(mlockall(MCL_CURRENT | MCL_FUTURE));
+	TEST_RES(locked_kb(), _ret > 0);
+	TEST_ERRNO(madvise(addr1, PAGE_SIZE, MADV_DONTNEED), EINVAL);
+
+	// The new mappings are locked as well.
+	addr2 = map_pages();
+	TEST_ERRNO(madvise(addr2, PAGE_SIZE, MADV_DONTNEED), EINVAL);
+
+	TEST_SUCC(munlockall());
+	TEST_RES(locked_kb(), _ret == 0);
+	TEST_SUCC(madvise(addr1, PAGE_SIZE, MADV_DONTNEED));
+	TEST_SUCC(madvise(addr2, PAGE_SIZE, MADV_DONTNEED));
+
+	TEST_SUCC(munmap(addr1, PAGE_SIZE * NR_PAGES));
+	TEST_SUCC(munmap(addr2, PAGE_SIZE * NR_PAGES));
+}
+END_TEST()
+
+FN_TEST(map_locked)
+{
+	char *addr;
+
+	addr = mmap(NULL, PAGE_SIZE * NR_PAGES, PROT_READ | PROT_WRITE,
+		    MAP_PRIVATE | MAP_ANONYMOUS | MAP_LOCKED, -1, 0);
+	CHECK(addr == MAP_FAILED ? -1 : 0);
+	TEST_RES(locked_kb(), _ret == PAGE_SIZE * NR_PAGES / 1024);
+
+	TEST_SUCC(munmap(addr, PAGE_SIZE * NR_PAGES));
+}
+END_TEST()
@@ -0,0 +1,87 @@
+// SPDX-License-Identifier: MPL-2.0
+
+#include <string.h>
+#include <sys/mman.h>
+#include <sys/wait.h>
+#include <unistd.h>
+
+#include "../network/test.h"
+
+#define PAGE_SIZE 4096
+#define NR_PAGES 16
+
+static char *addr;
+
+FN_SETUP(mmap_anonymous)
+{
+	addr = mmap(NULL, PAGE_SIZE * NR_PAGES, PROT_READ | PROT_WRITE,
+		    MAP_PRIVATE | MAP_ANONYMOUS, -1, 0);
+	CHECK(addr == MAP_FAILED ? -1 : 0);
+}
+END_SETUP()
+
+FN_TEST(read_then_write)
+{
+	int i;
+
+	// Pages that have only been read are all zeros.
+	for (i = 0; i < NR_PAGES; ++i)
+		TEST_RES(addr[i * PAGE_SIZE + 1], _ret == 0);
+
+	// Writing to one page does not affect other pages that have been read.
+	addr[1] = 'a';
+	addr[PAGE_SIZE * 2 + 1] = 'b';
+	TEST_RES(addr[1], _ret == 'a');
+	TEST_RES(addr[PAGE_SIZE + 1], _ret == 0);
+	TEST_RES(addr[PAGE_SIZE * 2 + 1], _ret == 'b');
+	TEST_RES(addr[PAGE_SIZE * 3 + 1], _ret == 0);
+}
+END_TEST()
+
+FN_TEST(read_then_mprotect)
+{
+	char *page = addr + PAGE_SIZE * 4;
+
+	// Pages that have been read stay zero after being made writable again.
+	TEST_SUCC(mprotect(page, PAGE_SIZE * 2, PROT_READ));
+	TEST_RES(page[1], _ret == 0);
+	TEST_RES(page[PAGE_SIZE + 1], _ret == 0);
+	TEST_SUCC(mprotect(page, PAGE_SIZE * 2, PROT_READ | PROT_WRITE));
+
+	page[1] = 'c';
+	TEST_RES(page[1], _ret == 'c');
+	TEST_RES(page[PAGE_SIZE + 1], _ret == 0);
+	TEST_RES(addr[PAGE_SIZE * 6 + 1], _ret == 0);
+}
+END_TEST()
+
+FN_TEST(read_then_fork)
+{
+	char *page = addr + PAGE_SIZE * 8;
+	int status;
+	pid_t pid;
+
+	TEST_RES(page[1], _ret == 0);
+	TEST_RES(page[PAGE_SIZE + 1], _ret == 0);
+
+	// Writes after fork are private to each process.
+	pid = TEST_SUCC(fork());
+	if (pid == 0) {
+		page[1] = 'd';
+		_exit(page[1] == 'd' && page[PAGE_SIZE + 1] == 0 ? EXIT_SUCCESS :
+								   EXIT_FAILURE);
+	}
+	page[PAGE_SIZE + 1] = 'e';
+	TEST_RES(waitpid(pid, &status, 0),
+		 _ret == pid && WIFEXITED(status) &&
+			 WEXITSTATUS(status) == EXIT_SUCCESS);
+	TEST_RES(page[1], _ret == 0);
+	TEST_RES(page[PAGE_SIZE + 1], _ret == 'e');
+}
+END_TEST()
+
+FN_SETUP(cleanup)
+{
+	CHECK(munmap(addr, PAGE_SIZE * NR_PAGES));
+}
+END_SETUP()
@@ -0,0 +1,182 @@
+// SPDX-License-Identifier: MPL-2.0
+
+#define _GNU_SOURCE
+
+#include <signal.h>
+#include <stdlib.h>
+#include <string.h>
+#include <sys/mman.h>
+#include <sys/wait.h>
+#include <unistd.h>
+
+#include "../network/test.h"
+
+#define PAGE_SIZE 4096
+
+static char *map_pages(char *addr, int nr_pages, int flags)
+{
+	char *res;
+
+	res = mmap(addr, PAGE_SIZE * nr_pages, PROT_READ | PROT_WRITE,
+		   MAP_PRIVATE | MAP_ANONYMOUS | flags, -1, 0);
+	CHECK(res == MAP_FAILED ? -1 : 0);
+
+	return res;
+}
+
+// Returns the signal that kills the child, or zero if the child exits normally.
+static int touch_in_child(char *addr)
+{
+	int pid, status;
+
+	pid = CHECK(fork());
+	if (pid == 0) {
+		*(volatile char *)addr = 'a';
+		exit(EXIT_SUCCESS);
+	}
+
+	CHECK_WITH(wait(&status), _ret == pid);
+	return WIFSIGNALED(status) ? WTERMSIG(status) : 0;
+}
+
+FN_TEST(resize_in_place)
+{
+	char *addr;
+
+	addr = map_pages(NULL, 4, 0);
+	strcpy(addr, "hello");
+	TEST_SUCC(munmap(addr + PAGE_SIZE * 2, PAGE_SIZE * 2));
+
+	// Grow in place.
+	TEST_RES((long)mremap(addr, PAGE_SIZE * 2, PAGE_SIZE * 4, 0),
+		 _ret == (long)addr);
+	TEST_RES(strcmp(addr, "hello"), _ret == 0);
+	addr[PAGE_SIZE * 3] = 'a';
+
+	// Shrink in place.
+	TEST_RES((long)mremap(addr, PAGE_SIZE * 4, PAGE_SIZE, 0),
+		 _ret == (long)addr);
+	TEST_RES(strcmp(addr, "hello"), _ret == 0);
+	TEST_RES((long)mmap(addr + PAGE_SIZE, PAGE_SIZE, PROT_READ,
+			    MAP_PRIVATE | MAP_ANONYMOUS | MAP_FIXED_NOREPLACE,
+			    -1, 0),
+		 _ret == (long)(addr + PAGE_SIZE));
+
+	// Cannot grow in place since the next page is occupied.
+	TEST_ERRNO((long)mremap(addr, PAGE_SIZE, PAGE_SIZE * 2, 0), ENOMEM);
+
+	TEST_SUCC(munmap(addr, PAGE_SIZE * 2));
+}
+END_TEST()
+
+FN_TEST(move)
+{
+	char *addr, *new_addr, *dst;
+
+	addr = map_pages(NULL, 2, 0);
+	strcpy(addr, "hello");
+	strcpy(addr + PAGE_SIZE, "world");
+
+	// Cannot grow in place, so the mapping is moved.
+	new_addr = (char *)TEST_RES(
+		(long)mremap(addr, PAGE_SIZE, PAGE_SIZE * 3, MREMAP_MAYMOVE),
+		_ret != (long)addr);
+	TEST_RES(strcmp(new_addr, "hello"), _ret == 0);
+	new_addr[PAGE_SIZE * 2] = 'a';
+	TEST_RES(strcmp(addr + PAGE_SIZE, "world"), _ret == 0);
+	TEST_RES(touch_in_child(addr), _ret == SIGSEGV);
+
+	// Move to a fixed address, replacing the existing mapping there.
+	dst = map_pages(NULL, 3, 0);
+	TEST_RES((long)mremap(new_addr, PAGE_SIZE * 3, PAGE_SIZE * 2,
+			      MREMAP_MAYMOVE | MREMAP_FIXED, dst),
+		 _ret == (long)dst);
+	TEST_RES(strcmp(dst, "hello"), _ret == 0);
+	TEST_RES(touch_in_child(new_addr), _ret == SIGSEGV);
+	TEST_RES(touch_in_child(dst + PAGE_SIZE * 2), _ret == 0);
+
+	TEST_SUCC(munmap(addr + PAGE_SIZE, PAGE_SIZE));
+	TEST_SUCC(munmap(dst, PAGE_SIZE * 3));
+}
+END_TEST()
+
+FN_TEST(invalid_mremap)
+{
+	char *addr;
+
+	addr = map_pages(NULL, 4, 0);
+	TEST_SUCC(munmap(addr + PAGE_SIZE * 3, PAGE_SIZE));
+
+	TEST_ERRNO((long)mremap(addr + 1, PAGE_SIZE, PAGE_SIZE, 0), EINVAL);
+	TEST_ERRNO((long)mremap(addr, PAGE_SIZE, 0, 0), EINVAL);
+	TEST_ERRNO((long)mremap(addr, PAGE_SIZE, PAGE_SIZE, MREMAP_FIXED,
+				addr + PAGE_SIZE * 2),
+		   EINVAL);
+	TEST_ERRNO((long)mremap(addr, PAGE_SIZE * 2, PAGE_SIZE,
+				MREMAP_MAYMOVE | MREMAP_FIXED,
+				addr + PAGE_SIZE),
+		   EINVAL);
+	TEST_ERRNO((long)mremap(addr, PAGE_SIZE, PAGE_SIZE, 0x80), EINVAL);
+
+	// The old range is not mapped.
+	TEST_ERRNO((long)mremap(addr + PAGE_SIZE * 3, PAGE_SIZE, PAGE_SIZE, 0),
+		   EFAULT);
+
+	TEST_SUCC(munmap(addr, PAGE_SIZE * 3));
+}
+END_TEST()
+
+FN_TEST(map_fixed_noreplace)
+{
+	char *addr;
+
+	addr = map_pages(NULL, 2, 0);
+	strcpy(addr, "hello");
+
+	TEST_ERRNO((long)mmap(addr + PAGE_SIZE, PAGE_SIZE, PROT_READ,
+			      MAP_PRIVATE | MAP_ANONYMOUS | MAP_FIXED_NOREPLACE,
+			      -1, 0),
+		   EEXIST);
+	TEST_RES(strcmp(addr, "hello"), _ret == 0);
+
+	TEST_SUCC(munmap(addr + PAGE_SIZE, PAGE_SIZE));
+	TEST_RES((long)mmap(addr + PAGE_SIZE, PAGE_SIZE, PROT_READ,
+			    MAP_PRIVATE | MAP_ANONYMOUS | MAP_FIXED_NOREPLACE,
+			    -1, 0),
+		 _ret == (long)(addr + PAGE_SIZE));
+
+	TEST_SUCC(munmap(addr, PAGE_SIZE * 2));
+}
+END_TEST()
+
+#define NR_RESERVED_PAGES 512
+
+FN_TEST(grows_down)
+{
+	char *base, *stack;
+
+	// Reserve a region that has enough space below the stack.
+	base = map_pages(NULL, NR_RESERVED_PAGES, 0);
+	TEST_SUCC(munmap(base, PAGE_SIZE * NR_RESERVED_PAGES));
+	stack = map_pages(base + PAGE_SIZE * (NR_RESERVED_PAGES - 1), 1,
+			  MAP_FIXED | MAP_GROWSDOWN);
+
+	// The stack grows down when the page below it is accessed.
+	stack[-1] = 'a';
+	stack[-PAGE_SIZE * 2] = 'b';
+	TEST_RES(stack[-1] + stack[-PAGE_SIZE * 2], _ret == 'a' + 'b');
+
+	// The stack cannot grow into the guard gap above another mapping.
+	map_pages(base + PAGE_SIZE * (NR_RESERVED_PAGES - 200), 1, MAP_FIXED);
+	TEST_RES(touch_in_child(stack - PAGE_SIZE * 3), _ret == SIGSEGV);
+
+	// The protection is applied to the start of the stack.
+	TEST_ERRNO(mprotect(base + PAGE_SIZE * (NR_RESERVED_PAGES - 200),
+			    PAGE_SIZE, PROT_READ | PROT_GROWSDOWN),
+		   EINVAL);
+	TEST_SUCC(mprotect(stack, PAGE_SIZE, PROT_READ | PROT_GROWSDOWN));
+	TEST_RES(touch_in_child(stack - PAGE_SIZE * 2), _ret == SIGSEGV);
+
+	TEST_SUCC(munmap(base, PAGE_SIZE * NR_RESERVED_PAGES));
+}
+END_TEST()
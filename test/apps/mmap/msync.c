@@ -0,0 +1,76 @@
+// SPDX-License-Identifier: MPL-2.0
+
+#include <string.h>
+#include <sys/mman.h>
+#include <sys/fcntl.h>
+#include <unistd.h>
+
+#include "../network/test.h"
+
+#define FILE_NAME "/tmp/msync.txt"
+
+#define PAGE_SIZE 4096
+#define NR_PAGES 4
+
+static int fd;
+static char *addr;
+
+FN_SETUP(msync)
+{
+	fd = CHECK(open(FILE_NAME, O_RDWR | O_CREAT | O_TRUNC, 0666));
+	CHECK(unlink(FILE_NAME));
+
+	CHECK(ftruncate(fd, PAGE_SIZE * NR_PAGES));
+
+	addr = mmap(NULL, PAGE_SIZE * (NR_PAGES + 1), PROT_READ | PROT_WRITE,
+		    MAP_SHARED, fd, 0);
+	CHECK(addr == MAP_FAILED ? -1 : 0);
+
+	// Leave an unmapped page after the mapping.
+	CHECK(munmap(addr + PAGE_SIZE * NR_PAGES, PAGE_SIZE));
+}
+END_SETUP()
+
+FN_TEST(invalid_args)
+{
+	TEST_ERRNO(msync(addr + 1, PAGE_SIZE, MS_SYNC), EINVAL);
+	TEST_ERRNO(msync(addr, PAGE_SIZE, MS_SYNC | MS_ASYNC), EINVAL);
+	TEST_ERRNO(msync(addr, PAGE_SIZE, 0x8), EINVAL);
+
+	// The range contains unmapped pages.
+	TEST_ERRNO(msync(addr, PAGE_SIZE * (NR_PAGES + 1), MS_SYNC), ENOMEM);
+
+	TEST_SUCC(msync(addr, 0, MS_SYNC));
+	TEST_SUCC(msync(addr, PAGE_SIZE * NR_PAGES, MS_ASYNC | MS_INVALIDATE));
+}
+END_TEST()
+
+FN_TEST(write_then_sync)
+{
+	char buf[16] = { 0 };
+
+	// The file is updated by writing to the mapping.
+	strcpy(addr + PAGE_SIZE + 1, "hello");
+	TEST_SUCC(msync(addr, PAGE_SIZE * NR_PAGES, MS_SYNC));
+	TEST_RES(pread(fd, buf, 6, PAGE_SIZE + 1),
+		 _ret == 6 && strcmp(buf, "hello") == 0);
+
+	// Pages that have been synchronized can be written again.
+	strcpy(addr + PAGE_SIZE + 1, "world");
+	TEST_SUCC(msync(addr + PAGE_SIZE, 10, MS_SYNC));
+	TEST_RES(pread(fd, buf, 6, PAGE_SIZE + 1),
+		 _ret == 6 && strcmp(buf, "world") == 0);
+	TEST_SUCC(fsync(fd));
+
+	// The mapping is updated by writing to the file.
+	TEST_RES(pwrite(fd, "mapped", 7, PAGE_SIZE * 3), _ret == 7);
+	TEST_RES(strcmp(addr + PAGE_SIZE * 3, "mapped"), _ret == 0);
+}
+END_TEST()
+
+FN_SETUP(cleanup)
+{
+	CHECK(munmap(addr, PAGE_SIZE * NR_PAGES));
+	CHECK(close(fd));
+}
+END_SETUP()
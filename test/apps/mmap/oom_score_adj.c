@@ -0,0 +1,83 @@
+// SPDX-License-Identifier: MPL-2.0
+
+#include <errno.h>
+#include <fcntl.h>
+#include <stdio.h>
+#include <stdlib.h>
+#include <string.h>
+#include <sys/wait.h>
+#include <unistd.h>
+
+#include "../network/test.h"
+
+#define OOM_SCORE_ADJ "/proc/self/oom_score_adj"
+#define OOM_SCORE "/proc/self/oom_score"
+
+static int read_value(const char *path)
+{
+	char buf[16] = { 0 };
+	int fd;
+
+	fd = CHECK(open(path, O_RDONLY));
+	CHECK(read(fd, buf, sizeof(buf) - 1));
+	CHECK(close(fd));
+
+	return atoi(buf);
+}
+
+static int write_value(const char *value)
+{
+	int fd, ret, err;
+
+	fd = CHECK(open(OOM_SCORE_ADJ, O_WRONLY));
+	ret = write(fd, value, strlen(value));
+	err = errno;
+	CHECK(close(fd));
+
+	errno = err;
+	return ret;
+}
+
+FN_TEST(read_write)
+{
+	TEST_RES(read_value(OOM_SCORE_ADJ), _ret == 0);
+	TEST_RES(read_value(OOM_SCORE), _ret >= 0 && _ret <= 1000);
+
+	TEST_RES(write_value("500\n"), _ret == 4);
+	TEST_RES(read_value(OOM_SCORE_ADJ), _ret == 500);
+	TEST_RES(read_value(OOM_SCORE), _ret >= 500);
+
+	TEST_RES(write_value("-1000"), _ret == 5);
+	TEST_RES(read_value(OOM_SCORE_ADJ), _ret == -1000);
+	TEST_RES(read_value(OOM_SCORE), _ret == 0);
+
+	TEST_RES(write_value("0"), _ret == 1);
+}
+END_TEST()
+
+FN_TEST(invalid_values)
+{
+	TEST_ERRNO(write_value("1001"), EINVAL);
+	TEST_ERRNO(write_value("-1001"), EINVAL);
+	TEST_ERRNO(write_value("abc"), EINVAL);
+	TEST_RES(read_value(OOM_SCORE_ADJ), _ret == 0);
+}
+END_TEST()
+
+FN_TEST(inherited_by_child)
+{
+	int pid, status;
+
+	TEST_RES(write_value("300"), _ret == 3);
+
+	pid = TEST_SUCC(fork());
+	if (pid == 0)
+		exit(read_value(OOM_SCORE_ADJ) == 300 ? EXIT_SUCCESS :
+							 EXIT_FAILURE);
+
+	TEST_RES(wait(&status), _ret == pid && WIFEXITED(status) &&
+					WEXITSTATUS(status) == EXIT_SUCCESS);
+
+	TEST_RES(write_value("0"), _ret == 1);
+}
+END_TEST()
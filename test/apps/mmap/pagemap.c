@@ -0,0 +1,119 @@
+// SPDX-License-Identifier: MPL-2.0
+
+#define _GNU_SOURCE
+
+#include <fcntl.h>
+#include <stdint.h>
+#include <stdio.h>
+#include <string.h>
+#include <sys/mman.h>
+#include <unistd.h>
+
+#include "../network/test.h"
+
+#define PAGE_SIZE 4096
+#define NR_PAGES 4
+
+#define PM_PRESENT (1ULL << 63)
+#define PM_FILE (1ULL << 61)
+
+static char *addr;
+
+FN_SETUP(map)
+{
+	addr = mmap(NULL, PAGE_SIZE * NR_PAGES, PROT_READ | PROT_WRITE,
+		    MAP_PRIVATE | MAP_ANONYMOUS, -1, 0);
+	CHECK(addr == MAP_FAILED ? -1 : 0);
+
+	// Only the first two pages are resident.
+	addr[0] = 1;
+	addr[PAGE_SIZE] = 1;
+}
+END_SETUP()
+
+static long smaps_field(const char *name)
+{
+	char line[256], header[32];
+	long start, end, value = -1;
+	int is_found = 0;
+	FILE *file;
+
+	file = fopen("/proc/self/smaps", "r");
+	if (file == NULL)
+		return -1;
+
+	snprintf(header, sizeof(header), "%s: %%ld kB", name);
+	while (fgets(line, sizeof(line), file)) {
+		if (sscanf(line, "%lx-%lx ", &start, &end) == 2) {
+			is_found = start == (long)addr;
+			continue;
+		}
+		if (is_found && sscanf(line, header, &value) == 1)
+			break;
+	}
+	fclose(file);
+
+	return value;
+}
+
+FN_TEST(smaps)
+{
+	TEST_RES(smaps_field("Size"), _ret == PAGE_SIZE * NR_PAGES / 1024);
+	TEST_RES(smaps_field("Rss"), _ret == PAGE_SIZE * 2 / 1024);
+	TEST_RES(smaps_field("Pss"), _ret == PAGE_SIZE * 2 / 1024);
+	TEST_RES(smaps_field("Private_Dirty"), _ret == PAGE_SIZE * 2 / 1024);
+	TEST_RES(smaps_field("Anonymous"), _ret == PAGE_SIZE * 2 / 1024);
+	TEST_RES(smaps_field("Swap"), _ret == 0);
+}
+END_TEST()
+
+FN_TEST(maps)
+{
+	char line[256], expected[64];
+	int is_found = 0;
+	FILE *file;
+
+	snprintf(expected, sizeof(expected), "%08lx-%08lx rw-p ",
+		 (unsigned long)addr,
+		 (unsigned long)addr + PAGE_SIZE * NR_PAGES);
+
+	file = fopen("/proc/self/maps", "r");
+	TEST_RES(file == NULL ? -1 : 0, _ret == 0);
+	while (fgets(line, sizeof(line), file))
+		if (strncmp(line, expected, strlen(expected)) == 0)
+			is_found = 1;
+	fclose(file);
+
+	TEST_RES(is_found, _ret == 1);
+}
+END_TEST()
+
+FN_TEST(pagemap)
+{
+	uint64_t entries[NR_PAGES];
+	off_t offset = (uintptr_t)addr / PAGE_SIZE * sizeof(uint64_t);
+	int fd;
+
+	fd = TEST_SUCC(open("/proc/self/pagemap", O_RDONLY));
+
+	TEST_RES(pread(fd, entries, sizeof(entries), offset),
+		 _ret == sizeof(entries));
+	TEST_RES(entries[0] & PM_PRESENT, _ret != 0);
+	TEST_RES(entries[1] & PM_PRESENT, _ret != 0);
+	TEST_RES(entries[2] & PM_PRESENT, _ret == 0);
+	TEST_RES(entries[3] & PM_PRESENT, _ret == 0);
+	TEST_RES(entries[0] & PM_FILE, _ret == 0);
+
+	// The reads must be aligned to the entries.
+	TEST_ERRNO(pread(fd, entries, sizeof(entries), offset + 1), EINVAL);
+	TEST_ERRNO(pread(fd, entries, 3, offset), EINVAL);
+
+	TEST_SUCC(close(fd));
+}
+END_TEST()
+
+FN_SETUP(unmap)
+{
+	CHECK(munmap(addr, PAGE_SIZE * NR_PAGES));
+}
+END_SETUP()
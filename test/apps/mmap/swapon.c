@@ -0,0 +1,198 @@
+// SPDX-License-Identifier: MPL-2.0
+
+#define _GNU_SOURCE
+
+#include <fcntl.h>
+#include <stdint.h>
+#include <stdio.h>
+#include <string.h>
+#include <sys/mman.h>
+#include <sys/swap.h>
+#include <sys/wait.h>
+#include <unistd.h>
+
+#include "../network/test.h"
+
+#define SWAP_FILE "/tmp/swapon.swp"
+#define BAD_SWAP_FILE "/tmp/swapon_bad.swp"
+
+#define PAGE_SIZE 4096
+#define NR_PAGES 16
+#define NR_SWAPPED_PAGES 8
+#define SWAPPED_SIZE (NR_SWAPPED_PAGES * PAGE_SIZE)
+
+static int is_in_proc_swaps(const char *path)
+{
+	char buf[1024] = { 0 };
+	int fd, found;
+
+	fd = CHECK(open("/proc/swaps", O_RDONLY));
+	CHECK(read(fd, buf, sizeof(buf) - 1));
+	found = strstr(buf, path) != NULL;
+	CHECK(close(fd));
+
+	return found;
+}
+
+static long swap_used_kb(const char *path)
+{
+	char buf[1024] = { 0 };
+	long size, used;
+	char *line;
+	int fd;
+
+	fd = CHECK(open("/proc/swaps", O_RDONLY));
+	CHECK(read(fd, buf, sizeof(buf) - 1));
+	CHECK(close(fd));
+
+	line = strstr(buf, path);
+	if (line == NULL ||
+	    sscanf(line + strlen(path), "%*s %ld %ld", &size, &used) != 2)
+		return -1;
+
+	return used;
+}
+
+static char *map_pages(void)
+{
+	char *addr;
+	int i;
+
+	addr = mmap(NULL, SWAPPED_SIZE, PROT_READ | PROT_WRITE,
+		    MAP_PRIVATE | MAP_ANONYMOUS, -1, 0);
+	CHECK(addr == MAP_FAILED ? -1 : 0);
+
+	for (i = 0; i < SWAPPED_SIZE; ++i)
+		addr[i] = i / PAGE_SIZE + 1;
+
+	return addr;
+}
+
+static int check_pages(const char *addr)
+{
+	int i;
+
+	for (i = 0; i < SWAPPED_SIZE; ++i)
+		if (addr[i] != (char)(i / PAGE_SIZE + 1))
+			return -1;
+
+	return 0;
+}
+
+FN_SETUP(swap_files)
+{
+	char header[PAGE_SIZE] = { 0 };
+	uint32_t version = 1, last_page = NR_PAGES - 1;
+	int fd, i;
+
+	// Write the header like `mkswap`.
+	memcpy(header + 1024, &version, sizeof(version));
+	memcpy(header + 1028, &last_page, sizeof(last_page));
+	memcpy(header + PAGE_SIZE - 10, "SWAPSPACE2", 10);
+
+	fd = CHECK(open(SWAP_FILE, O_WRONLY | O_CREAT | O_TRUNC, 0600));
+	CHECK(write(fd, header, PAGE_SIZE));
+	memset(header, 0, PAGE_SIZE);
+	for (i = 1; i < NR_PAGES; ++i)
+		CHECK(write(fd, header, PAGE_SIZE));
+	CHECK(close(fd));
+
+	// The file has no swap signature.
+	fd = CHECK(open(BAD_SWAP_FILE, O_WRONLY | O_CREAT | O_TRUNC, 0600));
+	for (i = 0; i < NR_PAGES; ++i)
+		CHECK(write(fd, header, PAGE_SIZE));
+	CHECK(close(fd));
+}
+END_SETUP()
+
+FN_TEST(swapon_swapoff)
+{
+	TEST_RES(is_in_proc_swaps(SWAP_FILE), _ret == 0);
+
+	TEST_SUCC(swapon(SWAP_FILE, 0));
+	TEST_RES(is_in_proc_swaps(SWAP_FILE), _ret == 1);
+	TEST_ERRNO(swapon(SWAP_FILE, 0), EBUSY);
+
+	TEST_SUCC(swapoff(SWAP_FILE));
+	TEST_RES(is_in_proc_swaps(SWAP_FILE), _ret == 0);
+	TEST_ERRNO(swapoff(SWAP_FILE), EINVAL);
+
+	// The priority can be specified.
+	TEST_SUCC(swapon(SWAP_FILE, SWAP_FLAG_PREFER | 5));
+	TEST_SUCC(swapoff(SWAP_FILE));
+}
+END_TEST()
+
+FN_TEST(page_out)
+{
+	char *addr;
+
+	TEST_SUCC(swapon(SWAP_FILE, 0));
+	addr = map_pages();
+
+	// The pages are swapped in when they are accessed.
+	TEST_SUCC(madvise(addr, SWAPPED_SIZE, MADV_PAGEOUT));
+	TEST_RES(swap_used_kb(SWAP_FILE), _ret == SWAPPED_SIZE / 1024);
+	TEST_SUCC(check_pages(addr));
+	TEST_RES(swap_used_kb(SWAP_FILE), _ret == 0);
+
+	// The pages are swapped in when the swap area is deactivated.
+	TEST_SUCC(madvise(addr, SWAPPED_SIZE, MADV_PAGEOUT));
+	TEST_RES(swap_used_kb(SWAP_FILE), _ret == SWAPPED_SIZE / 1024);
+	TEST_SUCC(swapoff(SWAP_FILE));
+	TEST_SUCC(check_pages(addr));
+
+	// Nothing is swapped out without active swap areas.
+	TEST_SUCC(madvise(addr, SWAPPED_SIZE, MADV_PAGEOUT));
+	TEST_SUCC(check_pages(addr));
+
+	// The locked pages cannot be swapped out.
+	TEST_SUCC(mlock(addr, SWAPPED_SIZE));
+	TEST_ERRNO(madvise(addr, SWAPPED_SIZE, MADV_PAGEOUT), EINVAL);
+
+	TEST_SUCC(munmap(addr, SWAPPED_SIZE));
+}
+END_TEST()
+
+FN_TEST(page_out_and_fork)
+{
+	char *addr;
+	int status;
+	pid_t pid;
+
+	TEST_SUCC(swapon(SWAP_FILE, 0));
+	addr = map_pages();
+	TEST_SUCC(madvise(addr, SWAPPED_SIZE, MADV_PAGEOUT));
+
+	// The swapped-out pages are shared with the child process.
+	pid = TEST_SUCC(fork());
+	if (pid == 0)
+		_exit(check_pages(addr) == 0 ? 0 : 1);
+	TEST_RES(wait4(pid, &status, 0, NULL),
+		 _ret == pid && WIFEXITED(status) && WEXITSTATUS(status) == 0);
+	TEST_RES(swap_used_kb(SWAP_FILE), _ret == SWAPPED_SIZE / 1024);
+
+	// The swap slots are freed when the pages are discarded.
+	TEST_SUCC(madvise(addr, SWAPPED_SIZE, MADV_DONTNEED));
+	TEST_RES(swap_used_kb(SWAP_FILE), _ret == 0);
+
+	TEST_SUCC(munmap(addr, SWAPPED_SIZE));
+	TEST_SUCC(swapoff(SWAP_FILE));
+}
+END_TEST()
+
+FN_TEST(invalid_swap_files)
+{
+	TEST_ERRNO(swapon(BAD_SWAP_FILE, 0), EINVAL);
+	TEST_ERRNO(swapon("/tmp", 0), EISDIR);
+	TEST_ERRNO(swapon(SWAP_FILE, 0x80000000), EINVAL);
+	TEST_ERRNO(swapon("/tmp/swapon_nonexistent.swp", 0), ENOENT);
+}
+END_TEST()
+
+FN_SETUP(cleanup)
+{
+	CHECK(unlink(SWAP_FILE));
+	CHECK(unlink(BAD_SWAP_FILE));
+}
+END_SETUP()
@@ -0,0 +1,138 @@
+// SPDX-License-Identifier: MPL-2.0
+
+#define _GNU_SOURCE
+
+#include <stdlib.h>
+#include <string.h>
+#include <sys/mman.h>
+#include <sys/wait.h>
+#include <unistd.h>
+
+#include "../network/test.h"
+
+#define PAGE_SIZE 4096
+#define HUGE_PAGE_SIZE (512 * PAGE_SIZE)
+#define MAP_SIZE (2 * HUGE_PAGE_SIZE)
+
+static char *map_huge_pages(void)
+{
+	char *addr;
+	size_t i;
+
+	addr = mmap(NULL, MAP_SIZE, PROT_READ | PROT_WRITE,
+		    MAP_PRIVATE | MAP_ANONYMOUS, -1, 0);
+	CHECK(addr == MAP_FAILED ? -1 : 0);
+
+	// The first write to each block maps a huge page.
+	for (i = 0; i < MAP_SIZE; i += PAGE_SIZE)
+		addr[i] = (char)(i / PAGE_SIZE);
+
+	return addr;
+}
+
+static int check_pages(char *addr, size_t start, size_t end)
+{
+	size_t i;
+
+	for (i = start; i < end; i += PAGE_SIZE)
+		if (addr[i] != (char)(i / PAGE_SIZE))
+			return -1;
+
+	return 0;
+}
+
+FN_TEST(aligned)
+{
+	char *addr = map_huge_pages();
+
+	TEST_RES((unsigned long)addr & (HUGE_PAGE_SIZE - 1), _ret == 0);
+	TEST_RES(check_pages(addr, 0, MAP_SIZE), _ret == 0);
+
+	TEST_SUCC(munmap(addr, MAP_SIZE));
+}
+END_TEST()
+
+FN_TEST(split_on_partial_changes)
+{
+	char *addr = map_huge_pages();
+
+	// Unmap a page in the middle of the first huge page.
+	TEST_SUCC(munmap(addr + PAGE_SIZE * 7, PAGE_SIZE));
+	TEST_RES(check_pages(addr, 0, PAGE_SIZE * 7), _ret == 0);
+	TEST_RES(check_pages(addr, PAGE_SIZE * 8, MAP_SIZE), _ret == 0);
+
+	// Protect a page in the middle of the second huge page.
+	TEST_SUCC(mprotect(addr + HUGE_PAGE_SIZE + PAGE_SIZE, PAGE_SIZE,
+			   PROT_READ));
+	addr[HUGE_PAGE_SIZE] = 'a';
+	addr[HUGE_PAGE_SIZE + PAGE_SIZE * 2] = 'b';
+	TEST_RES(addr[HUGE_PAGE_SIZE + PAGE_SIZE],
+		 _ret == (char)(HUGE_PAGE_SIZE / PAGE_SIZE + 1));
+
+	// Discard a page in the middle of the second huge page.
+	TEST_SUCC(madvise(addr + HUGE_PAGE_SIZE + PAGE_SIZE * 3, PAGE_SIZE,
+			  MADV_DONTNEED));
+	TEST_RES(addr[HUGE_PAGE_SIZE + PAGE_SIZE * 3], _ret == 0);
+	TEST_RES(check_pages(addr, HUGE_PAGE_SIZE + PAGE_SIZE * 4, MAP_SIZE),
+		 _ret == 0);
+
+	TEST_SUCC(munmap(addr, PAGE_SIZE * 7));
+	TEST_SUCC(munmap(addr + PAGE_SIZE * 8, MAP_SIZE - PAGE_SIZE * 8));
+}
+END_TEST()
+
+FN_TEST(advice)
+{
+	char *addr = map_huge_pages();
+
+	// The advices split the mapping in the middle of the huge pages.
+	TEST_SUCC(madvise(addr + PAGE_SIZE, HUGE_PAGE_SIZE, MADV_NOHUGEPAGE));
+	TEST_RES(check_pages(addr, 0, MAP_SIZE), _ret == 0);
+	TEST_SUCC(madvise(addr + PAGE_SIZE, HUGE_PAGE_SIZE, MADV_HUGEPAGE));
+	TEST_RES(check_pages(addr, 0, MAP_SIZE), _ret == 0);
+
+	TEST_SUCC(munmap(addr, MAP_SIZE));
+}
+END_TEST()
+
+FN_TEST(copy_on_write)
+{
+	char *addr = map_huge_pages();
+	int pid, status;
+
+	pid = CHECK(fork());
+	if (pid == 0) {
+		addr[PAGE_SIZE] = 'c';
+		if (addr[PAGE_SIZE] != 'c' ||
+		    check_pages(addr, PAGE_SIZE * 2, MAP_SIZE) < 0)
+			exit(EXIT_FAILURE);
+		exit(EXIT_SUCCESS);
+	}
+
+	TEST_RES(wait(&status),
+		 _ret == pid && WIFEXITED(status) && WEXITSTATUS(status) == 0);
+	TEST_RES(check_pages(addr, 0, MAP_SIZE), _ret == 0);
+
+	TEST_SUCC(munmap(addr, MAP_SIZE));
+}
+END_TEST()
+
+FN_TEST(move_unaligned)
+{
+	char *addr = map_huge_pages();
+	char *dst, *new_addr;
+
+	// Reserve a range whose start is not aligned to the huge page size.
+	dst = mmap(NULL, MAP_SIZE + HUGE_PAGE_SIZE, PROT_NONE,
+		   MAP_PRIVATE | MAP_ANONYMOUS, -1, 0);
+	TEST_RES((long)dst, dst != MAP_FAILED);
+	dst += PAGE_SIZE;
+
+	new_addr = mremap(addr, MAP_SIZE, MAP_SIZE,
+			  MREMAP_MAYMOVE | MREMAP_FIXED, dst);
+	TEST_RES((long)new_addr, new_addr == dst);
+	TEST_RES(check_pages(new_addr, 0, MAP_SIZE), _ret == 0);
+
+	TEST_SUCC(munmap(dst - PAGE_SIZE, MAP_SIZE + HUGE_PAGE_SIZE));
+}
+END_TEST()
@@ -0,0 +1,242 @@
+// SPDX-License-Identifier: MPL-2.0
+
+#define _GNU_SOURCE
+
+#include <fcntl.h>
+#include <linux/userfaultfd.h>
+#include <string.h>
+#include <sys/ioctl.h>
+#include <sys/mman.h>
+#include <sys/syscall.h>
+#include <sys/wait.h>
+#include <unistd.h>
+
+#include "../network/test.h"
+
+#define PAGE_SIZE 4096
+
+static int uffd;
+static char src[PAGE_SIZE];
+
+static int new_userfaultfd(int flags)
+{
+	return syscall(SYS_userfaultfd, flags | UFFD_USER_MODE_ONLY);
+}
+
+static char *map_pages(int nr_pages)
+{
+	char *addr;
+
+	addr = mmap(NULL, PAGE_SIZE * nr_pages, PROT_READ | PROT_WRITE,
+		    MAP_PRIVATE | MAP_ANONYMOUS, -1, 0);
+	CHECK(addr == MAP_FAILED ? -1 : 0);
+
+	return addr;
+}
+
+static int register_range(char *addr, size_t len, __u64 mode)
+{
+	struct uffdio_register reg = {
+		.range = { .start = (unsigned long)addr, .len = len },
+		.mode = mode,
+	};
+
+	return ioctl(uffd, UFFDIO_REGISTER, &reg);
+}
+
+static int copy_page(char *dst, __u64 mode, long long *copied)
+{
+	struct uffdio_copy copy = {
+		.dst = (unsigned long)dst,
+		.src = (unsigned long)src,
+		.len = PAGE_SIZE,
+		.mode = mode,
+	};
+	int ret;
+
+	ret = ioctl(uffd, UFFDIO_COPY, &copy);
+	*copied = copy.copy;
+	return ret;
+}
+
+static int write_protect(char *addr, size_t len, __u64 mode)
+{
+	struct uffdio_writeprotect wp = {
+		.range = { .start = (unsigned long)addr, .len = len },
+		.mode = mode,
+	};
+
+	return ioctl(uffd, UFFDIO_WRITEPROTECT, &wp);
+}
+
+FN_TEST(api)
+{
+	struct uffdio_api api = { .api = 0x1 };
+	char *addr = map_pages(1);
+
+	uffd = TEST_SUCC(new_userfaultfd(O_CLOEXEC));
+
+	// Other ioctls are not allowed before the API is negotiated.
+	TEST_ERRNO(register_range(addr, PAGE_SIZE,
+				  UFFDIO_REGISTER_MODE_MISSING),
+		   EINVAL);
+
+	TEST_ERRNO(ioctl(uffd, UFFDIO_API, &api), EINVAL);
+	api.api = UFFD_API;
+	TEST_RES(ioctl(uffd, UFFDIO_API, &api),
+		 api.ioctls & (1ULL << _UFFDIO_REGISTER));
+	TEST_ERRNO(ioctl(uffd, UFFDIO_API, &api), EINVAL);
+
+	TEST_SUCC(munmap(addr, PAGE_SIZE));
+}
+END_TEST()
+
+FN_TEST(invalid_args)
+{
+	struct uffd_msg msg;
+	long long copied;
+	char *addr = map_pages(2);
+
+	TEST_ERRNO(register_range(addr + 1, PAGE_SIZE,
+				  UFFDIO_REGISTER_MODE_MISSING),
+		   EINVAL);
+	TEST_ERRNO(register_range(addr, 0, UFFDIO_REGISTER_MODE_MISSING),
+		   EINVAL);
+	TEST_ERRNO(register_range(addr, PAGE_SIZE, 0), EINVAL);
+
+	// The range is not registered.
+	TEST_ERRNO(copy_page(addr, 0, &copied), ENOENT);
+	TEST_ERRNO(write_protect(addr, PAGE_SIZE, UFFDIO_WRITEPROTECT_MODE_WP),
+		   ENOENT);
+
+	// The range is not registered in the write-protect mode.
+	TEST_SUCC(register_range(addr, PAGE_SIZE,
+				 UFFDIO_REGISTER_MODE_MISSING));
+	TEST_ERRNO(write_protect(addr, PAGE_SIZE, UFFDIO_WRITEPROTECT_MODE_WP),
+		   ENOENT);
+
+	TEST_ERRNO(read(uffd, &msg, sizeof(msg) - 1), EINVAL);
+	TEST_SUCC(fcntl(uffd, F_SETFL, O_NONBLOCK));
+	TEST_ERRNO(read(uffd, &msg, sizeof(msg)), EAGAIN);
+	TEST_SUCC(fcntl(uffd, F_SETFL, 0));
+
+	TEST_SUCC(munmap(addr, PAGE_SIZE * 2));
+}
+END_TEST()
+
+FN_TEST(missing_page)
+{
+	struct uffdio_register reg;
+	struct uffdio_zeropage zeropage;
+	struct uffd_msg msg;
+	long long copied;
+	pid_t pid;
+	int status;
+	char *addr = map_pages(2);
+
+	reg.range.start = (unsigned long)addr;
+	reg.range.len = PAGE_SIZE * 2;
+	reg.mode = UFFDIO_REGISTER_MODE_MISSING;
+	TEST_RES(ioctl(uffd, UFFDIO_REGISTER, &reg),
+		 reg.ioctls & (1ULL << _UFFDIO_COPY));
+
+	memset(src, 'a', PAGE_SIZE);
+
+	pid = CHECK(fork());
+	if (pid == 0) {
+		// The child process resolves the page fault of the parent.
+		CHECK_WITH(read(uffd, &msg, sizeof(msg)), _ret == sizeof(msg));
+		CHECK_WITH(msg.event == UFFD_EVENT_PAGEFAULT &&
+				   msg.arg.pagefault.address ==
+					   (unsigned long)addr,
+			   _ret);
+		CHECK(copy_page(addr, 0, &copied));
+		_exit(0);
+	}
+
+	// The access is blocked until the page is copied.
+	TEST_RES(addr[1], _ret == 'a');
+	TEST_RES(wait(&status), _ret == pid && WIFEXITED(status) &&
+					WEXITSTATUS(status) == 0);
+
+	// The page has been mapped.
+	TEST_ERRNO(copy_page(addr, 0, &copied), EEXIST);
+	TEST_RES(copied, _ret == -EEXIST);
+
+	zeropage.range.start = (unsigned long)addr + PAGE_SIZE;
+	zeropage.range.len = PAGE_SIZE;
+	zeropage.mode = 0;
+	TEST_RES(ioctl(uffd, UFFDIO_ZEROPAGE, &zeropage),
+		 zeropage.zeropage == PAGE_SIZE);
+	TEST_RES(addr[PAGE_SIZE], _ret == 0);
+
+	// The pages are writable.
+	addr[0] = 'b';
+	addr[PAGE_SIZE] = 'c';
+	TEST_RES(addr[0] + addr[PAGE_SIZE], _ret == 'b' + 'c');
+
+	TEST_SUCC(munmap(addr, PAGE_SIZE * 2));
+}
+END_TEST()
+
+FN_TEST(write_protect)
+{
+	struct uffdio_range range;
+	struct uffd_msg msg;
+	long long copied;
+	pid_t pid;
+	int status;
+	char *addr = map_pages(1);
+
+	TEST_SUCC(register_range(addr, PAGE_SIZE,
+				 UFFDIO_REGISTER_MODE_MISSING |
+					 UFFDIO_REGISTER_MODE_WP));
+
+	memset(src, 'x', PAGE_SIZE);
+	TEST_RES(copy_page(addr, UFFDIO_COPY_MODE_WP, &copied),
+		 copied == PAGE_SIZE);
+
+	// Reading a write-protected page is allowed.
+	TEST_RES(addr[0], _ret == 'x');
+
+	pid = CHECK(fork());
+	if (pid == 0) {
+		// The child process removes the protection after the write.
+		CHECK_WITH(read(uffd, &msg, sizeof(msg)), _ret == sizeof(msg));
+		CHECK_WITH(msg.arg.pagefault.flags &
+				   UFFD_PAGEFAULT_FLAG_WP,
+			   _ret);
+		CHECK(write_protect(addr, PAGE_SIZE, 0));
+		_exit(0);
+	}
+
+	// The write is blocked until the protection is removed.
+	addr[0] = 'y';
+	TEST_RES(addr[0], _ret == 'y');
+	TEST_RES(wait(&status), _ret == pid && WIFEXITED(status) &&
+					WEXITSTATUS(status) == 0);
+
+	// The protection can be enabled again.
+	TEST_SUCC(write_protect(addr, PAGE_SIZE, UFFDIO_WRITEPROTECT_MODE_WP));
+	TEST_ERRNO(write_protect(addr, PAGE_SIZE,
+				 UFFDIO_WRITEPROTECT_MODE_WP |
+					 UFFDIO_WRITEPROTECT_MODE_DONTWAKE),
+		   EINVAL);
+
+	range.start = (unsigned long)addr;
+	range.len = PAGE_SIZE;
+	TEST_SUCC(ioctl(uffd, UFFDIO_UNREGISTER, &range));
+
+	// The protection is removed after unregistering the range.
+	addr[0] = 'z';
+	TEST_RES(addr[0], _ret == 'z');
+
+	TEST_SUCC(munmap(addr, PAGE_SIZE));
+}
+END_TEST()
+
+FN_SETUP(cleanup)
+{
+	CHECK(close(uffd));
+}
+END_SETUP()
@@ -0,0 +1,141 @@
+// SPDX-License-Identifier: MPL-2.0
+
+#define _GNU_SOURCE
+
+#include <fcntl.h>
+#include <stdio.h>
+#include <stdlib.h>
+#include <string.h>
+#include <sys/mman.h>
+#include <sys/personality.h>
+#include <sys/wait.h>
+#include <unistd.h>
+
+#include "../network/test.h"
+
+#define PAGE_SIZE 4096
+#define WX_POLICY_PATH "/proc/sys/vm/wx_policy"
+
+// Returns the W^X policy, or zero if the policy is not supported.
+static int wx_policy(void)
+{
+	char buf[4] = { 0 };
+	int fd;
+
+	fd = open(WX_POLICY_PATH, O_RDONLY);
+	if (fd < 0)
+		return 0;
+	if (read(fd, buf, sizeof(buf) - 1) < 0)
+		buf[0] = '0';
+	close(fd);
+
+	return buf[0] - '0';
+}
+
+FN_TEST(jit)
+{
+	char *addr;
+
+	// Code is written to a writable mapping and then made executable.
+	addr = mmap(NULL, PAGE_SIZE, PROT_READ | PROT_WRITE,
+		    MAP_PRIVATE | MAP_ANONYMOUS, -1, 0);
+	CHECK(addr == MAP_FAILED ? -1 : 0);
+#if defined(__x86_64__)
+	// mov $42, %eax; ret
+	memcpy(addr, "\xb8\x2a\x00\x00\x00\xc3", 6);
+#endif
+	TEST_SUCC(mprotect(addr, PAGE_SIZE, PROT_READ | PROT_EXEC));
+#if defined(__x86_64__)
+	TEST_RES(((int (*)(void))addr)(), _ret == 42);
+#endif
+
+	// The code can be modified again after it is made writable.
+	TEST_SUCC(mprotect(addr, PAGE_SIZE, PROT_READ | PROT_WRITE));
+#if defined(__x86_64__)
+	addr[1] = 43;
+#endif
+	TEST_SUCC(mprotect(addr, PAGE_SIZE, PROT_READ | PROT_EXEC));
+#if defined(__x86_64__)
+	TEST_RES(((int (*)(void))addr)(), _ret == 43);
+#endif
+
+	TEST_SUCC(munmap(addr, PAGE_SIZE));
+}
+END_TEST()
+
+FN_TEST(wx_denied)
+{
+	char *addr;
+
+	if (wx_policy() < 2)
+		return;
+
+	TEST_ERRNO((long)mmap(NULL, PAGE_SIZE,
+			      PROT_READ | PROT_WRITE | PROT_EXEC,
+			      MAP_PRIVATE | MAP_ANONYMOUS, -1, 0),
+		   EACCES);
+
+	addr = mmap(NULL, PAGE_SIZE, PROT_READ | PROT_WRITE,
+		    MAP_PRIVATE | MAP_ANONYMOUS, -1, 0);
+	CHECK(addr == MAP_FAILED ? -1 : 0);
+	TEST_ERRNO(mprotect(addr, PAGE_SIZE,
+			    PROT_READ | PROT_WRITE | PROT_EXEC),
+		   EACCES);
+	TEST_ERRNO(mprotect(addr, PAGE_SIZE, PROT_WRITE | PROT_EXEC), EACCES);
+	TEST_SUCC(munmap(addr, PAGE_SIZE));
+}
+END_TEST()
+
+// Returns whether the mapping at the address is writable and executable.
+static int is_wx_mapping(void *addr)
+{
+	unsigned long start, end;
+	char perms[5], line[256];
+	int found = 0;
+	FILE *maps;
+
+	maps = fopen("/proc/self/maps", "r");
+	if (maps == NULL)
+		return 0;
+	while (fgets(line, sizeof(line), maps) != NULL) {
+		if (sscanf(line, "%lx-%lx %4s", &start, &end, perms) != 3)
+			continue;
+		if (start <= (unsigned long)addr && (unsigned long)addr < end) {
+			found = perms[1] == 'w' && perms[2] == 'x';
+			break;
+		}
+	}
+	fclose(maps);
+
+	return found;
+}
+
+FN_TEST(read_implies_exec)
+{
+	int status;
+	pid_t pid;
+
+	pid = TEST_SUCC(fork());
+	if (pid == 0) {
+		void *addr;
+
+		// The processes with `READ_IMPLIES_EXEC` are exempted from the
+		// policy, and their readable mappings are executable.
+		if (personality(READ_IMPLIES_EXEC) < 0)
+			_exit(EXIT_FAILURE);
+		addr = mmap(NULL, PAGE_SIZE, PROT_READ | PROT_WRITE,
+			    MAP_PRIVATE | MAP_ANONYMOUS, -1, 0);
+		if (addr == MAP_FAILED || !is_wx_mapping(addr))
+			_exit(EXIT_FAILURE);
+		addr = mmap(NULL, PAGE_SIZE, PROT_READ | PROT_WRITE | PROT_EXEC,
+			    MAP_PRIVATE | MAP_ANONYMOUS, -1, 0);
+		if (addr == MAP_FAILED || !is_wx_mapping(addr))
+			_exit(EXIT_FAILURE);
+		_exit(EXIT_SUCCESS);
+	}
+
+	TEST_RES(waitpid(pid, &status, 0),
+		 _ret == pid && WIFEXITED(status) &&
+			 WEXITSTATUS(status) == EXIT_SUCCESS);
+}
+END_TEST()
@@ -0,0 +1,275 @@
+// SPDX-License-Identifier: MPL-2.0
+
+#define _GNU_SOURCE
+
+#include <fcntl.h>
+#include <mqueue.h>
+#include <signal.h>
+#include <string.h>
+#include <sys/wait.h>
+#include <time.h>
+#include <unistd.h>
+
+#include "../network/test.h"
+
+#define QUEUE_NAME "/mqueue_test"
+#define MAX_MSGS 4
+#define MSG_SIZE 16
+
+static mqd_t create_queue(int flags)
+{
+	struct mq_attr attr = { .mq_maxmsg = MAX_MSGS, .mq_msgsize = MSG_SIZE };
+
+	return mq_open(QUEUE_NAME, O_CREAT | O_EXCL | flags, 0600, &attr);
+}
+
+static struct timespec deadline_after_ms(long ms)
+{
+	struct timespec ts;
+
+	clock_gettime(CLOCK_REALTIME, &ts);
+	ts.tv_nsec += ms * 1000000;
+	ts.tv_sec += ts.tv_nsec / 1000000000;
+	ts.tv_nsec %= 1000000000;
+
+	return ts;
+}
+
+static int wait_exit_code(pid_t pid)
+{
+	int status;
+
+	if (waitpid(pid, &status, 0) != pid)
+		return -1;
+	if (!WIFEXITED(status))
+		return -1;
+
+	return WEXITSTATUS(status);
+}
+
+FN_TEST(open_and_unlink)
+{
+	struct mq_attr attr = { .mq_maxmsg = 0, .mq_msgsize = MSG_SIZE };
+	mqd_t mqd;
+
+	mqd = TEST_SUCC(create_queue(O_RDWR));
+	TEST_ERRNO(create_queue(O_RDWR), EEXIST);
+	TEST_RES(fcntl(mqd, F_GETFD), _ret & FD_CLOEXEC);
+	TEST_RES(mq_getattr(mqd, &attr),
+		 attr.mq_flags == 0 && attr.mq_maxmsg == MAX_MSGS &&
+			 attr.mq_msgsize == MSG_SIZE && attr.mq_curmsgs == 0);
+	TEST_SUCC(mq_close(mqd));
+
+	// The queue is still there after its descriptors are closed.
+	mqd = TEST_SUCC(mq_open(QUEUE_NAME, O_RDONLY));
+	TEST_SUCC(mq_close(mqd));
+
+	TEST_SUCC(mq_unlink(QUEUE_NAME));
+	TEST_ERRNO(mq_unlink(QUEUE_NAME), ENOENT);
+	TEST_ERRNO(mq_open(QUEUE_NAME, O_RDONLY), ENOENT);
+
+	TEST_ERRNO(mq_open("/mqueue/test", O_CREAT | O_RDWR, 0600, NULL),
+		   EACCES);
+	attr.mq_maxmsg = 0;
+	TEST_ERRNO(mq_open(QUEUE_NAME, O_CREAT | O_RDWR, 0600, &attr), EINVAL);
+	attr.mq_maxmsg = MAX_MSGS;
+	attr.mq_msgsize = -1;
+	TEST_ERRNO(mq_open(QUEUE_NAME, O_CREAT | O_RDWR, 0600, &attr), EINVAL);
+}
+END_TEST()
+
+FN_TEST(send_and_receive)
+{
+	char buf[MSG_SIZE * 2];
+	unsigned int prio;
+	mqd_t mqd, mqd_wronly;
+
+	mqd = TEST_SUCC(create_queue(O_RDWR));
+	TEST_SUCC(mq_unlink(QUEUE_NAME));
+
+	TEST_SUCC(mq_send(mqd, "low", 3, 1));
+	TEST_SUCC(mq_send(mqd, "high1", 5, 5));
+	TEST_SUCC(mq_send(mqd, "mid", 3, 3));
+	TEST_SUCC(mq_send(mqd, "high2", 5, 5));
+	TEST_ERRNO(mq_send(mqd, buf, MSG_SIZE + 1, 0), EMSGSIZE);
+	TEST_ERRNO(mq_send(mqd, "bad", 3, sysconf(_SC_MQ_PRIO_MAX)), EINVAL);
+
+	// The buffer must be able to hold the largest message.
+	TEST_ERRNO(mq_receive(mqd, buf, MSG_SIZE - 1, &prio), EMSGSIZE);
+
+	// The messages with the highest priority are received first.
+	TEST_RES(mq_receive(mqd, buf, sizeof(buf), &prio),
+		 _ret == 5 && prio == 5 && !memcmp(buf, "high1", 5));
+	TEST_RES(mq_receive(mqd, buf, sizeof(buf), &prio),
+		 _ret == 5 && prio == 5 && !memcmp(buf, "high2", 5));
+	TEST_RES(mq_receive(mqd, buf, sizeof(buf), &prio),
+		 _ret == 3 && prio == 3 && !memcmp(buf, "mid", 3));
+	TEST_RES(mq_receive(mqd, buf, sizeof(buf), NULL),
+		 _ret == 3 && !memcmp(buf, "low", 3));
+
+	// The access mode of the descriptor is checked.
+	mqd_wronly = TEST_SUCC(create_queue(O_WRONLY));
+	TEST_SUCC(mq_unlink(QUEUE_NAME));
+	TEST_ERRNO(mq_receive(mqd_wronly, buf, sizeof(buf), NULL), EBADF);
+	TEST_ERRNO(mq_send(0, "bad", 3, 0), EBADF);
+
+	TEST_SUCC(mq_close(mqd_wronly));
+	TEST_SUCC(mq_close(mqd));
+}
+END_TEST()
+
+FN_TEST(nonblocking_and_timeout)
+{
+	struct mq_attr attr, old_attr;
+	struct timespec ts;
+	char buf[MSG_SIZE];
+	mqd_t mqd;
+	int i;
+
+	mqd = TEST_SUCC(create_queue(O_RDWR | O_NONBLOCK));
+	TEST_SUCC(mq_unlink(QUEUE_NAME));
+
+	TEST_ERRNO(mq_receive(mqd, buf, sizeof(buf), NULL), EAGAIN);
+	for (i = 0; i < MAX_MSGS; ++i)
+		TEST_SUCC(mq_send(mqd, "msg", 3, 0));
+	TEST_ERRNO(mq_send(mqd, "msg", 3, 0), EAGAIN);
+	TEST_RES(mq_getattr(mqd, &attr),
+		 attr.mq_flags == O_NONBLOCK && attr.mq_curmsgs == MAX_MSGS);
+
+	// Only the flags can be changed.
+	attr.mq_flags = 0;
+	attr.mq_maxmsg = 1;
+	TEST_RES(mq_setattr(mqd, &attr, &old_attr),
+		 old_attr.mq_flags == O_NONBLOCK &&
+			 old_attr.mq_maxmsg == MAX_MSGS);
+	TEST_RES(mq_getattr(mqd, &attr),
+		 attr.mq_flags == 0 && attr.mq_maxmsg == MAX_MSGS);
+	attr.mq_flags = O_APPEND;
+	TEST_ERRNO(mq_setattr(mqd, &attr, NULL), EINVAL);
+
+	ts = deadline_after_ms(10);
+	TEST_ERRNO(mq_timedsend(mqd, "msg", 3, 0, &ts), ETIMEDOUT);
+	for (i = 0; i < MAX_MSGS; ++i)
+		TEST_SUCC(mq_receive(mqd, buf, sizeof(buf), NULL));
+	ts = deadline_after_ms(10);
+	TEST_ERRNO(mq_timedreceive(mqd, buf, sizeof(buf), NULL, &ts),
+		   ETIMEDOUT);
+	ts.tv_nsec = 1000000000;
+	TEST_ERRNO(mq_timedreceive(mqd, buf, sizeof(buf), NULL, &ts), EINVAL);
+
+	TEST_SUCC(mq_close(mqd));
+}
+END_TEST()
+
+FN_TEST(blocking_receive)
+{
+	char buf[MSG_SIZE];
+	unsigned int prio;
+	mqd_t mqd;
+	pid_t pid;
+
+	mqd = TEST_SUCC(create_queue(O_RDWR));
+	TEST_SUCC(mq_unlink(QUEUE_NAME));
+
+	pid = TEST_SUCC(fork());
+	if (pid == 0) {
+		usleep(100 * 1000);
+		if (mq_send(mqd, "wake", 4, 7) < 0)
+			_exit(1);
+		_exit(0);
+	}
+
+	TEST_RES(mq_receive(mqd, buf, sizeof(buf), &prio),
+		 _ret == 4 && prio == 7 && !memcmp(buf, "wake", 4));
+	TEST_RES(wait_exit_code(pid), _ret == 0);
+
+	TEST_SUCC(mq_close(mqd));
+}
+END_TEST()
+
+FN_TEST(notify_signal)
+{
+	struct sigevent sev = {
+		.sigev_notify = SIGEV_SIGNAL,
+		.sigev_signo = SIGUSR1,
+		.sigev_value.sival_int = 42,
+	};
+	char buf[MSG_SIZE];
+	siginfo_t info;
+	sigset_t set;
+	mqd_t mqd;
+	pid_t pid;
+
+	sigemptyset(&set);
+	sigaddset(&set, SIGUSR1);
+	TEST_SUCC(sigprocmask(SIG_BLOCK, &set, NULL));
+
+	mqd = TEST_SUCC(create_queue(O_RDWR));
+	TEST_SUCC(mq_unlink(QUEUE_NAME));
+
+	TEST_SUCC(mq_notify(mqd, &sev));
+	TEST_ERRNO(mq_notify(mqd, &sev), EBUSY);
+
+	// Other processes cannot register or remove the notification.
+	pid = TEST_SUCC(fork());
+	if (pid == 0) {
+		if (mq_notify(mqd, &sev) == 0 || errno != EBUSY)
+			_exit(1);
+		if (mq_notify(mqd, NULL) < 0)
+			_exit(1);
+		_exit(0);
+	}
+	TEST_RES(wait_exit_code(pid), _ret == 0);
+
+	TEST_SUCC(mq_send(mqd, "msg", 3, 0));
+	TEST_RES(sigwaitinfo(&set, &info),
+		 _ret == SIGUSR1 && info.si_code == SI_MESGQ &&
+			 info.si_pid == getpid() &&
+			 info.si_value.sival_int == 42);
+
+	// The notification is removed after it is triggered.
+	TEST_SUCC(mq_notify(mqd, &sev));
+	// The notification is not triggered if the queue is not empty.
+	TEST_SUCC(mq_send(mqd, "msg", 3, 0));
+	TEST_RES(sigpending(&set), !sigismember(&set, SIGUSR1));
+	TEST_SUCC(mq_notify(mqd, NULL));
+
+	TEST_SUCC(mq_receive(mqd, buf, sizeof(buf), NULL));
+	TEST_SUCC(mq_receive(mqd, buf, sizeof(buf), NULL));
+	TEST_SUCC(mq_send(mqd, "msg", 3, 0));
+	TEST_RES(sigpending(&set), !sigismember(&set, SIGUSR1));
+
+	sev.sigev_notify = SIGEV_THREAD_ID;
+	TEST_ERRNO(mq_notify(mqd, &sev), EINVAL);
+	sev.sigev_notify = SIGEV_SIGNAL;
+	sev.sigev_signo = 100;
+	TEST_ERRNO(mq_notify(mqd, &sev), EINVAL);
+
+	TEST_SUCC(mq_close(mqd));
+	sigemptyset(&set);
+	sigaddset(&set, SIGUSR1);
+	TEST_SUCC(sigprocmask(SIG_UNBLOCK, &set, NULL));
+}
+END_TEST()
+
+FN_TEST(notify_removed_on_close)
+{
+	struct sigevent sev = {
+		.sigev_notify = SIGEV_SIGNAL,
+		.sigev_signo = SIGUSR1,
+	};
+	mqd_t mqd, mqd2;
+
+	mqd = TEST_SUCC(create_queue(O_RDWR));
+	mqd2 = TEST_SUCC(mq_open(QUEUE_NAME, O_RDWR));
+	TEST_SUCC(mq_unlink(QUEUE_NAME));
+
+	// Closing any descriptor of the queue removes the notification.
+	TEST_SUCC(mq_notify(mqd, &sev));
+	TEST_SUCC(mq_close(mqd2));
+	TEST_SUCC(mq_notify(mqd, &sev));
+	TEST_SUCC(mq_notify(mqd, NULL));
+
+	TEST_SUCC(mq_close(mqd));
+}
+END_TEST()
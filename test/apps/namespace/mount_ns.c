@@ -0,0 +1,248 @@
+// SPDX-License-Identifier: MPL-2.0
+
+#define _GNU_SOURCE
+
+#include "../network/test.h"
+
+#include <fcntl.h>
+#include <pthread.h>
+#include <sched.h>
+#include <sys/mount.h>
+#include <sys/stat.h>
+#include <sys/wait.h>
+#include <unistd.h>
+
+#define BASE "/tmp/mount_ns"
+#define SRC BASE "/src"
+#define DATA BASE "/data"
+#define DATA2 BASE "/data2"
+#define DIR_A BASE "/a"
+#define DIR_B BASE "/b"
+#define DIR_C BASE "/c"
+
+static int create_file(const char *path)
+{
+	int fd;
+
+	fd = open(path, O_CREAT | O_WRONLY, 0644);
+	if (fd < 0)
+		return -1;
+	return close(fd);
+}
+
+static int wait_exit_code(pid_t pid)
+{
+	int status;
+
+	if (waitpid(pid, &status, 0) != pid || !WIFEXITED(status))
+		return -1;
+	return WEXITSTATUS(status);
+}
+
+FN_SETUP(dirs)
+{
+	CHECK(mkdir(BASE, 0755));
+	CHECK(mkdir(SRC, 0755));
+	CHECK(mkdir(SRC "/sub", 0755));
+	CHECK(mkdir(SRC "/sub2", 0755));
+	CHECK(create_file(SRC "/file"));
+	CHECK(mkdir(DATA, 0755));
+	CHECK(create_file(DATA "/data_file"));
+	CHECK(mkdir(DATA2, 0755));
+	CHECK(create_file(DATA2 "/data2_file"));
+	CHECK(mkdir(DIR_A, 0755));
+	CHECK(mkdir(DIR_B, 0755));
+	CHECK(mkdir(DIR_C, 0755));
+}
+END_SETUP()
+
+FN_TEST(bind_mount)
+{
+	TEST_ERRNO(access(DIR_A "/file", F_OK), ENOENT);
+	TEST_SUCC(mount(SRC, DIR_A, NULL, MS_BIND, NULL));
+	TEST_SUCC(access(DIR_A "/file", F_OK));
+
+	TEST_SUCC(umount(DIR_A));
+	TEST_ERRNO(access(DIR_A "/file", F_OK), ENOENT);
+}
+END_TEST()
+
+FN_TEST(move_mount)
+{
+	TEST_SUCC(mount(SRC, DIR_A, NULL, MS_BIND, NULL));
+	TEST_SUCC(mount(DIR_A, DIR_C, NULL, MS_MOVE, NULL));
+	TEST_ERRNO(access(DIR_A "/file", F_OK), ENOENT);
+	TEST_SUCC(access(DIR_C "/file", F_OK));
+
+	// Only the root of a mount can be moved.
+	TEST_ERRNO(mount(DIR_C "/sub", DIR_A, NULL, MS_MOVE, NULL), EINVAL);
+
+	TEST_SUCC(umount(DIR_C));
+	TEST_ERRNO(access(DIR_C "/file", F_OK), ENOENT);
+}
+END_TEST()
+
+FN_TEST(shared_propagation)
+{
+	TEST_SUCC(mount(SRC, DIR_A, NULL, MS_BIND, NULL));
+	TEST_SUCC(mount(NULL, DIR_A, NULL, MS_SHARED, NULL));
+	// The bind mount of a shared mount is a peer of it.
+	TEST_SUCC(mount(DIR_A, DIR_B, NULL, MS_BIND, NULL));
+
+	// Mounts and unmounts are propagated between the peers.
+	TEST_SUCC(mount(DATA, DIR_A "/sub", NULL, MS_BIND, NULL));
+	TEST_SUCC(access(DIR_B "/sub/data_file", F_OK));
+	TEST_SUCC(umount(DIR_B "/sub"));
+	TEST_ERRNO(access(DIR_A "/sub/data_file", F_OK), ENOENT);
+
+	TEST_SUCC(umount(DIR_B));
+	TEST_SUCC(umount(DIR_A));
+}
+END_TEST()
+
+FN_TEST(slave_propagation)
+{
+	TEST_SUCC(mount(SRC, DIR_A, NULL, MS_BIND, NULL));
+	TEST_SUCC(mount(NULL, DIR_A, NULL, MS_SHARED, NULL));
+	TEST_SUCC(mount(DIR_A, DIR_B, NULL, MS_BIND, NULL));
+	TEST_SUCC(mount(NULL, DIR_B, NULL, MS_SLAVE, NULL));
+
+	// Mounts are propagated from the master to the slave only.
+	TEST_SUCC(mount(DATA, DIR_A "/sub", NULL, MS_BIND, NULL));
+	TEST_SUCC(access(DIR_B "/sub/data_file", F_OK));
+	TEST_SUCC(mount(DATA, DIR_B "/sub2", NULL, MS_BIND, NULL));
+	TEST_ERRNO(access(DIR_A "/sub2/data_file", F_OK), ENOENT);
+
+	TEST_SUCC(umount(DIR_A "/sub"));
+	TEST_ERRNO(access(DIR_B "/sub/data_file", F_OK), ENOENT);
+
+	// A propagated mount does not replace an existing mount of the slave.
+	TEST_SUCC(mount(DATA2, DIR_A "/sub2", NULL, MS_BIND, NULL));
+	TEST_SUCC(access(DIR_A "/sub2/data2_file", F_OK));
+	TEST_SUCC(access(DIR_B "/sub2/data_file", F_OK));
+	TEST_ERRNO(access(DIR_B "/sub2/data2_file", F_OK), ENOENT);
+
+	TEST_SUCC(umount(DIR_A "/sub2"));
+	TEST_SUCC(umount(DIR_B "/sub2"));
+	TEST_SUCC(umount(DIR_B));
+	TEST_SUCC(umount(DIR_A));
+}
+END_TEST()
+
+FN_TEST(recursive_propagation)
+{
+	TEST_SUCC(mount(SRC, DIR_A, NULL, MS_BIND, NULL));
+	TEST_SUCC(mount(NULL, DIR_A, NULL, MS_SHARED, NULL));
+	TEST_SUCC(mount(DIR_A, DIR_B, NULL, MS_BIND, NULL));
+
+	TEST_SUCC(mount(SRC, DIR_C, NULL, MS_BIND, NULL));
+	TEST_SUCC(mount(DATA, DIR_C "/sub", NULL, MS_BIND, NULL));
+
+	// The submounts of a recursive bind mount are propagated as well.
+	TEST_SUCC(mount(DIR_C, DIR_A "/sub2", NULL, MS_BIND | MS_REC, NULL));
+	TEST_SUCC(access(DIR_A "/sub2/sub/data_file", F_OK));
+	TEST_SUCC(access(DIR_B "/sub2/sub/data_file", F_OK));
+
+	TEST_SUCC(umount(DIR_B "/sub2/sub"));
+	TEST_SUCC(umount(DIR_A "/sub2/sub"));
+	TEST_SUCC(umount(DIR_A "/sub2"));
+	TEST_ERRNO(access(DIR_B "/sub2/file", F_OK), ENOENT);
+
+	TEST_SUCC(umount(DIR_C "/sub"));
+	TEST_SUCC(umount(DIR_C));
+	TEST_SUCC(umount(DIR_B));
+	TEST_SUCC(umount(DIR_A));
+}
+END_TEST()
+
+static int mount_in_new_ns(void)
+{
+	if (unshare(CLONE_NEWNS) < 0)
+		return 1;
+	if (mount(SRC, DIR_A, NULL, MS_BIND, NULL) < 0)
+		return 2;
+	if (access(DIR_A "/file", F_OK) < 0)
+		return 3;
+	return 0;
+}
+
+FN_TEST(unshare_newns)
+{
+	pid_t pid;
+
+	pid = TEST_SUCC(fork());
+	if (pid == 0)
+		_exit(mount_in_new_ns());
+	TEST_RES(wait_exit_code(pid), _ret == 0);
+
+	// The mount in the new mount namespace is invisible here.
+	TEST_ERRNO(access(DIR_A "/file", F_OK), ENOENT);
+}
+END_TEST()
+
+static void *chdir_after_unshare_fs(void *arg)
+{
+	if (unshare(CLONE_FS) < 0 || chdir(SRC) < 0)
+		return (void *)1;
+	return NULL;
+}
+
+FN_TEST(unshare_fs)
+{
+	char cwd[64];
+	pthread_t thread;
+	void *ret;
+
+	TEST_SUCC(chdir(BASE));
+	TEST_RES(pthread_create(&thread, NULL, chdir_after_unshare_fs, NULL),
+		 _ret == 0);
+	TEST_RES(pthread_join(thread, &ret), _ret == 0 && ret == NULL);
+
+	// The thread changed its own working directory only.
+	TEST_RES(getcwd(cwd, sizeof(cwd)) != NULL, _ret);
+	TEST_RES(strcmp(cwd, BASE), _ret == 0);
+	TEST_SUCC(chdir("/"));
+}
+END_TEST()
+
+static void *close_after_unshare_files(void *arg)
+{
+	int fd = *(int *)arg;
+
+	if (unshare(CLONE_FILES) < 0 || close(fd) < 0)
+		return (void *)1;
+	return NULL;
+}
+
+FN_TEST(unshare_files)
+{
+	pthread_t thread;
+	void *ret;
+	int fd;
+
+	fd = TEST_SUCC(open(SRC "/file", O_RDONLY));
+	TEST_RES(pthread_create(&thread, NULL, close_after_unshare_files, &fd),
+		 _ret == 0);
+	TEST_RES(pthread_join(thread, &ret), _ret == 0 && ret == NULL);
+
+	// The thread closed the file in its own file table only.
+	TEST_SUCC(close(fd));
+}
+END_TEST()
+
+static int unshare_sysvsem(void)
+{
+	return unshare(CLONE_SYSVSEM) < 0;
+}
+
+FN_TEST(unshare_sysvsem)
+{
+	pid_t pid;
+
+	// The semaphore adjustments of a single-threaded process can be unshared.
+	pid = TEST_SUCC(fork());
+	if (pid == 0)
+		_exit(unshare_sysvsem());
+	TEST_RES(wait_exit_code(pid), _ret == 0);
+}
+END_TEST()
@@ -0,0 +1,192 @@
+// SPDX-License-Identifier: MPL-2.0
+
+#define _GNU_SOURCE
+
+#include "../network/test.h"
+
+#include <fcntl.h>
+#include <linux/reboot.h>
+#include <sched.h>
+#include <signal.h>
+#include <sys/syscall.h>
+#include <sys/wait.h>
+#include <unistd.h>
+
+static int wait_exit_code(pid_t pid)
+{
+	int status;
+
+	if (waitpid(pid, &status, 0) != pid)
+		return -1;
+	if (!WIFEXITED(status))
+		return -1;
+
+	return WEXITSTATUS(status);
+}
+
+// Runs in the first process of the new PID namespace. Returns the number of
+// failed checks.
+static int init_process(void)
+{
+	pid_t pid;
+	int failures = 0;
+
+	failures += getpid() != 1;
+	failures += syscall(SYS_gettid) != 1;
+	// The parent is in the parent PID namespace, so it is invisible.
+	failures += getppid() != 0;
+
+	pid = fork();
+	if (pid == 0)
+		_exit(getpid() == 2 && getppid() == 1 ? 0 : 1);
+	failures += pid != 2;
+	failures += wait_exit_code(pid) != 0;
+
+	// The PIDs are allocated per namespace.
+	pid = fork();
+	if (pid == 0)
+		_exit(0);
+	failures += pid != 3;
+	failures += wait_exit_code(pid) != 0;
+
+	// The processes outside the namespace are invisible.
+	failures += kill(100, 0) != -1 || errno != ESRCH;
+
+	return failures;
+}
+
+FN_TEST(unshare_newpid)
+{
+	pid_t pid, child;
+	int pipefd[2];
+
+	TEST_SUCC(pipe(pipefd));
+
+	pid = TEST_SUCC(fork());
+	if (pid == 0) {
+		pid_t self = getpid();
+
+		close(pipefd[0]);
+		if (unshare(CLONE_NEWPID) < 0)
+			_exit(1);
+		// The PID namespace of the current process does not change.
+		if (getpid() != self)
+			_exit(1);
+
+		child = fork();
+		if (child == 0)
+			_exit(init_process());
+		if (write(pipefd[1], &child, sizeof(child)) != sizeof(child))
+			_exit(1);
+		// The child is visible with its PID in the parent namespace.
+		_exit(wait_exit_code(child));
+	}
+	close(pipefd[1]);
+
+	// The PID in the parent namespace is not 1.
+	TEST_RES(read(pipefd[0], &child, sizeof(child)),
+		 _ret == sizeof(child) && child != 1);
+	TEST_RES(wait_exit_code(pid), _ret == 0);
+
+	TEST_SUCC(close(pipefd[0]));
+}
+END_TEST()
+
+static int clone_init(void *arg)
+{
+	(void)arg;
+
+	return init_process();
+}
+
+static char stack[65536];
+
+FN_TEST(clone_newpid)
+{
+	pid_t pid;
+
+	pid = TEST_SUCC(clone(clone_init, stack + sizeof(stack),
+			      CLONE_NEWPID | SIGCHLD, NULL));
+	TEST_RES(wait_exit_code(pid), _ret == 0);
+}
+END_TEST()
+
+FN_TEST(kill_on_init_exit)
+{
+	pid_t pid;
+
+	pid = TEST_SUCC(fork());
+	if (pid == 0) {
+		pid_t init, child;
+
+		if (unshare(CLONE_NEWPID) < 0)
+			_exit(1);
+
+		init = fork();
+		if (init == 0) {
+			// The child outlives the init process until it is
+			// killed. The init process exits without waiting for
+			// it.
+			if (fork() == 0) {
+				pause();
+				_exit(0);
+			}
+			_exit(0);
+		}
+		if (wait_exit_code(init) != 0)
+			_exit(1);
+
+		// No new processes can be created in the namespace.
+		child = fork();
+		if (child == 0)
+			_exit(0);
+		if (child >= 0 || errno != ENOMEM)
+			_exit(1);
+
+		_exit(0);
+	}
+	TEST_RES(wait_exit_code(pid), _ret == 0);
+}
+END_TEST()
+
+static int reboot_init(void *arg)
+{
+	(void)arg;
+
+	// Only the commands that stop the namespace are allowed.
+	if (syscall(SYS_reboot, LINUX_REBOOT_MAGIC1, LINUX_REBOOT_MAGIC2,
+		    LINUX_REBOOT_CMD_CAD_OFF, NULL) != -1 ||
+	    errno != EINVAL)
+		return 1;
+
+	// Powering off kills all the processes in the namespace.
+	syscall(SYS_reboot, LINUX_REBOOT_MAGIC1, LINUX_REBOOT_MAGIC2,
+		LINUX_REBOOT_CMD_POWER_OFF, NULL);
+
+	return 2;
+}
+
+FN_TEST(reboot_in_pid_ns)
+{
+	int status;
+	pid_t pid;
+
+	TEST_ERRNO(syscall(SYS_reboot, LINUX_REBOOT_MAGIC1, 0,
+			   LINUX_REBOOT_CMD_POWER_OFF, NULL),
+		   EINVAL);
+
+	pid = TEST_SUCC(clone(reboot_init, stack + sizeof(stack),
+			      CLONE_NEWPID | SIGCHLD, NULL));
+	TEST_RES(waitpid(pid, &status, 0), _ret == pid && WIFSIGNALED(status));
+}
+END_TEST()
+
+FN_TEST(invalid_flags)
+{
+	TEST_ERRNO(clone(clone_init, stack + sizeof(stack),
+			 CLONE_NEWPID | CLONE_THREAD | CLONE_SIGHAND |
+				 CLONE_VM,
+			 NULL),
+		   EINVAL);
+}
+END_TEST()
@@ -0,0 +1,134 @@
+// SPDX-License-Identifier: MPL-2.0
+
+#define _GNU_SOURCE
+
+#include "../network/test.h"
+
+#include <fcntl.h>
+#include <sched.h>
+#include <sys/ipc.h>
+#include <sys/sem.h>
+#include <sys/utsname.h>
+#include <sys/wait.h>
+#include <unistd.h>
+
+#define SEM_KEY 0x4e53
+#define HOSTNAME "namespace-test"
+
+static struct utsname uts;
+static char hostname[sizeof(uts.nodename)];
+
+static int wait_exit_code(pid_t pid)
+{
+	int status;
+
+	if (waitpid(pid, &status, 0) != pid)
+		return -1;
+	if (!WIFEXITED(status))
+		return -1;
+
+	return WEXITSTATUS(status);
+}
+
+static int has_hostname(const char *name)
+{
+	return uname(&uts) == 0 && strcmp(uts.nodename, name) == 0;
+}
+
+FN_SETUP(hostname)
+{
+	CHECK(gethostname(hostname, sizeof(hostname)));
+}
+END_SETUP()
+
+FN_TEST(sethostname)
+{
+	char long_name[sizeof(uts.nodename)];
+
+	memset(long_name, 'a', sizeof(long_name));
+	TEST_ERRNO(sethostname(long_name, sizeof(long_name)), EINVAL);
+	TEST_ERRNO(setdomainname(long_name, sizeof(long_name)), EINVAL);
+}
+END_TEST()
+
+FN_TEST(unshare_newuts)
+{
+	pid_t pid;
+
+	pid = TEST_SUCC(fork());
+	if (pid == 0) {
+		if (unshare(CLONE_NEWUTS) < 0)
+			_exit(1);
+		// The new namespace starts with the names of the old one.
+		if (!has_hostname(hostname))
+			_exit(1);
+		if (sethostname(HOSTNAME, strlen(HOSTNAME)) < 0)
+			_exit(1);
+		if (!has_hostname(HOSTNAME))
+			_exit(1);
+		_exit(0);
+	}
+	TEST_RES(wait_exit_code(pid), _ret == 0);
+
+	// The host name outside the namespace does not change.
+	TEST_RES(has_hostname(hostname), _ret);
+}
+END_TEST()
+
+FN_TEST(unshare_newipc)
+{
+	int semid;
+	pid_t pid;
+
+	semid = TEST_SUCC(semget(SEM_KEY, 1, IPC_CREAT | 0600));
+
+	pid = TEST_SUCC(fork());
+	if (pid == 0) {
+		if (semget(SEM_KEY, 1, 0) != semid)
+			_exit(1);
+		if (unshare(CLONE_NEWIPC) < 0)
+			_exit(1);
+		// The semaphore set is invisible in the new namespace.
+		if (semget(SEM_KEY, 1, 0) != -1 || errno != ENOENT)
+			_exit(1);
+		if (semget(SEM_KEY, 1, IPC_CREAT | 0600) < 0)
+			_exit(1);
+		_exit(0);
+	}
+	TEST_RES(wait_exit_code(pid), _ret == 0);
+
+	TEST_RES(semget(SEM_KEY, 1, 0), _ret == semid);
+	TEST_SUCC(semctl(semid, 0, IPC_RMID));
+}
+END_TEST()
+
+FN_TEST(setns)
+{
+	int uts_fd, file_fd;
+	pid_t pid;
+
+	uts_fd = TEST_SUCC(open("/proc/self/ns/uts", O_RDONLY));
+	file_fd = TEST_SUCC(open("/proc/self/ns", O_RDONLY));
+
+	TEST_ERRNO(setns(file_fd, 0), EINVAL);
+	TEST_ERRNO(setns(uts_fd, CLONE_NEWIPC), EINVAL);
+
+	pid = TEST_SUCC(fork());
+	if (pid == 0) {
+		if (unshare(CLONE_NEWUTS) < 0)
+			_exit(1);
+		if (sethostname(HOSTNAME, strlen(HOSTNAME)) < 0)
+			_exit(1);
+		// Enter the namespace of the parent again.
+		if (setns(uts_fd, CLONE_NEWUTS) < 0)
+			_exit(1);
+		if (!has_hostname(hostname))
+			_exit(1);
+		_exit(0);
+	}
+	TEST_RES(wait_exit_code(pid), _ret == 0);
+
+	TEST_SUCC(close(file_fd));
+	TEST_SUCC(close(uts_fd));
+}
+END_TEST()
@@ -0,0 +1,175 @@
+// SPDX-License-Identifier: MPL-2.0
+
+#include <unistd.h>
+#include <string.h>
+#include <sys/socket.h>
+#include <netinet/in.h>
+#include <arpa/inet.h>
+
+#include "test.h"
+
+#define TCP_PORT htons(0x2345)
+#define UDP_PORT htons(0x2346)
+#define UNUSED_PORT htons(0x2347)
+
+static struct sockaddr_in6 lo_addr;
+
+FN_SETUP(general)
+{
+	lo_addr.sin6_family = AF_INET6;
+	lo_addr.sin6_addr = in6addr_loopback;
+}
+END_SETUP()
+
+FN_TEST(getsockname_unbound)
+{
+	struct sockaddr_in6 addr;
+	socklen_t addrlen = sizeof(addr);
+	int sk;
+
+	sk = TEST_SUCC(socket(AF_INET6, SOCK_STREAM, 0));
+	TEST_RES(getsockname(sk, (struct sockaddr *)&addr, &addrlen),
+		 addrlen == sizeof(addr) && addr.sin6_family == AF_INET6 &&
+			 addr.sin6_port == 0 &&
+			 IN6_IS_ADDR_UNSPECIFIED(&addr.sin6_addr));
+	TEST_SUCC(close(sk));
+
+	sk = TEST_SUCC(socket(AF_INET6, SOCK_DGRAM, 0));
+	addrlen = sizeof(addr);
+	TEST_RES(getsockname(sk, (struct sockaddr *)&addr, &addrlen),
+		 addrlen == sizeof(addr) && addr.sin6_family == AF_INET6 &&
+			 addr.sin6_port == 0 &&
+			 IN6_IS_ADDR_UNSPECIFIED(&addr.sin6_addr));
+	TEST_SUCC(close(sk));
+}
+END_TEST()
+
+FN_TEST(family_mismatch)
+{
+	struct sockaddr_in6 addr6 = lo_addr;
+	int sk4, sk6;
+
+	sk4 = TEST_SUCC(socket(AF_INET, SOCK_DGRAM, 0));
+	sk6 = TEST_SUCC(socket(AF_INET6, SOCK_DGRAM, 0));
+
+	addr6.sin6_port = UNUSED_PORT;
+	TEST_ERRNO(connect(sk4, (struct sockaddr *)&addr6, sizeof(addr6)),
+		   EAFNOSUPPORT);
+	TEST_ERRNO(sendto(sk4, "x", 1, 0, (struct sockaddr *)&addr6,
+			  sizeof(addr6)),
+		   EAFNOSUPPORT);
+
+	addr6.sin6_family = AF_INET;
+	TEST_ERRNO(bind(sk6, (struct sockaddr *)&addr6, sizeof(addr6)),
+		   EAFNOSUPPORT);
+
+	TEST_SUCC(close(sk4));
+	TEST_SUCC(close(sk6));
+}
+END_TEST()
+
+FN_TEST(bind_unavailable)
+{
+	struct sockaddr_in6 addr = lo_addr;
+	int sk;
+
+	sk = TEST_SUCC(socket(AF_INET6, SOCK_STREAM, 0));
+
+	CHECK(inet_pton(AF_INET6, "2001:db8::1", &addr.sin6_addr));
+	TEST_ERRNO(bind(sk, (struct sockaddr *)&addr, sizeof(addr)),
+		   EADDRNOTAVAIL);
+
+	TEST_ERRNO(bind(sk, (struct sockaddr *)&lo_addr,
+			sizeof(lo_addr) - sizeof(lo_addr.sin6_scope_id) - 1),
+		   EINVAL);
+
+	TEST_SUCC(close(sk));
+}
+END_TEST()
+
+FN_TEST(tcp_refused)
+{
+	struct sockaddr_in6 addr = lo_addr;
+	int sk;
+
+	sk = TEST_SUCC(socket(AF_INET6, SOCK_STREAM, 0));
+
+	addr.sin6_port = UNUSED_PORT;
+	TEST_ERRNO(connect(sk, (struct sockaddr *)&addr, sizeof(addr)),
+		   ECONNREFUSED);
+
+	TEST_SUCC(close(sk));
+}
+END_TEST()
+
+FN_TEST(tcp_loopback)
+{
+	struct sockaddr_in6 addr = lo_addr;
+	struct sockaddr_in6 peer_addr;
+	socklen_t addrlen;
+	int sk_listen, sk_connect, sk_accept;
+	char buf[6];
+
+	sk_listen = TEST_SUCC(socket(AF_INET6, SOCK_STREAM, 0));
+	sk_connect = TEST_SUCC(socket(AF_INET6, SOCK_STREAM, 0));
+
+	addr.sin6_port = TCP_PORT;
+	TEST_SUCC(bind(sk_listen, (struct sockaddr *)&addr, sizeof(addr)));
+	TEST_SUCC(listen(sk_listen, 1));
+
+	TEST_SUCC(connect(sk_connect, (struct sockaddr *)&addr, sizeof(addr)));
+
+	addrlen = sizeof(peer_addr);
+	sk_accept = TEST_RES(
+		accept(sk_listen, (struct sockaddr *)&peer_addr, &addrlen),
+		addrlen == sizeof(peer_addr) &&
+			peer_addr.sin6_family == AF_INET6 &&
+			IN6_IS_ADDR_LOOPBACK(&peer_addr.sin6_addr));
+
+	addrlen = sizeof(addr);
+	TEST_RES(getpeername(sk_connect, (struct sockaddr *)&addr, &addrlen),
+		 addrlen == sizeof(addr) && addr.sin6_family == AF_INET6 &&
+			 addr.sin6_port == TCP_PORT &&
+			 IN6_IS_ADDR_LOOPBACK(&addr.sin6_addr));
+
+	TEST_RES(send(sk_connect, "hello", 6, 0), _ret == 6);
+	TEST_RES(recv(sk_accept, buf, sizeof(buf), 0),
+		 _ret == 6 && strcmp(buf, "hello") == 0);
+
+	TEST_SUCC(close(sk_accept));
+	TEST_SUCC(close(sk_connect));
+	TEST_SUCC(close(sk_listen));
+}
+END_TEST()
+
+FN_TEST(udp_loopback)
+{
+	struct sockaddr_in6 addr = lo_addr;
+	struct sockaddr_in6 src_addr;
+	socklen_t addrlen;
+	int sk_recv, sk_send;
+	char buf[6];
+
+	sk_recv = TEST_SUCC(socket(AF_INET6, SOCK_DGRAM, 0));
+	sk_send = TEST_SUCC(socket(AF_INET6, SOCK_DGRAM, 0));
+
+	addr.sin6_port = UDP_PORT;
+	TEST_SUCC(bind(sk_recv, (struct sockaddr *)&addr, sizeof(addr)));
+
+	TEST_RES(sendto(sk_send, "world", 6, 0, (struct sockaddr *)&addr,
+			sizeof(addr)),
+		 _ret == 6);
+
+	addrlen = sizeof(src_addr);
+	TEST_RES(recvfrom(sk_recv, buf, sizeof(buf), 0,
+			  (struct sockaddr *)&src_addr, &addrlen),
+		 _ret == 6 && strcmp(buf, "world") == 0 &&
+			 addrlen == sizeof(src_addr) &&
+			 src_addr.sin6_family == AF_INET6 &&
+			 src_addr.sin6_port != 0 &&
+			 IN6_IS_ADDR_LOOPBACK(&src_addr.sin6_addr));
+
+	TEST_SUCC(close(sk_recv));
+	TEST_SUCC(close(sk_send));
+}
+END_TEST()
@@ -0,0 +1,168 @@
+// SPDX-License-Identifier: MPL-2.0
+
+#include <string.h>
+#include <sys/socket.h>
+#include <netinet/in.h>
+#include <arpa/inet.h>
+#include <unistd.h>
+
+#include "test.h"
+
+#define GROUP_ADDR "239.1.2.3"
+#define GROUP_PORT 5353
+
+static int sk_recv;
+static int sk_send;
+static struct sockaddr_in group_addr;
+static struct ip_mreqn group_mreqn;
+
+FN_SETUP(socket)
+{
+	sk_recv = CHECK(socket(AF_INET, SOCK_DGRAM, 0));
+	sk_send = CHECK(socket(AF_INET, SOCK_DGRAM, 0));
+
+	group_addr.sin_family = AF_INET;
+	group_addr.sin_port = htons(GROUP_PORT);
+	CHECK(inet_aton(GROUP_ADDR, &group_addr.sin_addr));
+
+	group_mreqn.imr_multiaddr = group_addr.sin_addr;
+	group_mreqn.imr_address.s_addr = htonl(INADDR_ANY);
+	group_mreqn.imr_ifindex = 0;
+}
+END_SETUP()
+
+FN_TEST(default_options)
+{
+	int val;
+	struct in_addr iface_addr;
+	socklen_t optlen;
+
+	optlen = sizeof(val);
+	TEST_RES(getsockopt(sk_send, SOL_IP, IP_MULTICAST_TTL, &val, &optlen),
+		 optlen == sizeof(val) && val == 1);
+
+	optlen = sizeof(val);
+	TEST_RES(getsockopt(sk_send, SOL_IP, IP_MULTICAST_LOOP, &val, &optlen),
+		 optlen == sizeof(val) && val == 1);
+
+	optlen = sizeof(iface_addr);
+	TEST_RES(getsockopt(sk_send, SOL_IP, IP_MULTICAST_IF, &iface_addr,
+			    &optlen),
+		 optlen == sizeof(iface_addr) &&
+			 iface_addr.s_addr == htonl(INADDR_ANY));
+}
+END_TEST()
+
+FN_TEST(multicast_ttl)
+{
+	int ttl;
+	socklen_t optlen = sizeof(ttl);
+
+	ttl = 256;
+	TEST_ERRNO(setsockopt(sk_send, SOL_IP, IP_MULTICAST_TTL, &ttl,
+			      sizeof(ttl)),
+		   EINVAL);
+
+	ttl = 5;
+	TEST_SUCC(setsockopt(sk_send, SOL_IP, IP_MULTICAST_TTL, &ttl,
+			     sizeof(ttl)));
+	TEST_RES(getsockopt(sk_send, SOL_IP, IP_MULTICAST_TTL, &ttl, &optlen),
+		 optlen == sizeof(ttl) && ttl == 5);
+
+	// -1 restores the default TTL.
+	ttl = -1;
+	TEST_SUCC(setsockopt(sk_send, SOL_IP, IP_MULTICAST_TTL, &ttl,
+			     sizeof(ttl)));
+	TEST_RES(getsockopt(sk_send, SOL_IP, IP_MULTICAST_TTL, &ttl, &optlen),
+		 optlen == sizeof(ttl) && ttl == 1);
+}
+END_TEST()
+
+FN_TEST(invalid_membership)
+{
+	struct ip_mreqn mreqn = group_mreqn;
+
+	TEST_ERRNO(setsockopt(sk_recv, SOL_IP, IP_ADD_MEMBERSHIP, &mreqn,
+			      sizeof(struct in_addr)),
+		   EINVAL);
+
+	mreqn.imr_multiaddr.s_addr = htonl(INADDR_LOOPBACK);
+	TEST_ERRNO(setsockopt(sk_recv, SOL_IP, IP_ADD_MEMBERSHIP, &mreqn,
+			      sizeof(mreqn)),
+		   EINVAL);
+
+	TEST_ERRNO(setsockopt(sk_recv, SOL_IP, IP_DROP_MEMBERSHIP, &group_mreqn,
+			      sizeof(group_mreqn)),
+		   EADDRNOTAVAIL);
+}
+END_TEST()
+
+FN_TEST(join_group)
+{
+	struct ip_mreq mreq = { .imr_multiaddr = group_mreqn.imr_multiaddr,
+				.imr_interface = group_mreqn.imr_address };
+
+	TEST_SUCC(bind(sk_recv, (struct sockaddr *)&group_addr,
+		       sizeof(group_addr)));
+
+	TEST_SUCC(setsockopt(sk_recv, SOL_IP, IP_ADD_MEMBERSHIP, &group_mreqn,
+			     sizeof(group_mreqn)));
+
+	// The legacy `struct ip_mreq` refers to the same membership.
+	TEST_ERRNO(setsockopt(sk_recv, SOL_IP, IP_ADD_MEMBERSHIP, &mreq,
+			      sizeof(mreq)),
+		   EADDRINUSE);
+}
+END_TEST()
+
+FN_TEST(multicast_loop)
+{
+	char buf[6];
+	int loop;
+
+	// The multicast packets are looped back to the local sockets by default.
+	TEST_RES(sendto(sk_send, "hello", 6, 0, (struct sockaddr *)&group_addr,
+			sizeof(group_addr)),
+		 _ret == 6);
+	TEST_RES(recv(sk_recv, buf, sizeof(buf), 0),
+		 _ret == 6 && strcmp(buf, "hello") == 0);
+
+	loop = 0;
+	TEST_SUCC(setsockopt(sk_send, SOL_IP, IP_MULTICAST_LOOP, &loop,
+			     sizeof(loop)));
+
+	TEST_RES(sendto(sk_send, "world", 6, 0, (struct sockaddr *)&group_addr,
+			sizeof(group_addr)),
+		 _ret == 6);
+	TEST_ERRNO(recv(sk_recv, buf, sizeof(buf), MSG_DONTWAIT), EAGAIN);
+
+	loop = 1;
+	TEST_SUCC(setsockopt(sk_send, SOL_IP, IP_MULTICAST_LOOP, &loop,
+			     sizeof(loop)));
+}
+END_TEST()
+
+FN_TEST(leave_group)
+{
+	char buf[6];
+
+	TEST_SUCC(setsockopt(sk_recv, SOL_IP, IP_DROP_MEMBERSHIP, &group_mreqn,
+			     sizeof(group_mreqn)));
+	TEST_ERRNO(setsockopt(sk_recv, SOL_IP, IP_DROP_MEMBERSHIP, &group_mreqn,
+			      sizeof(group_mreqn)),
+		   EADDRNOTAVAIL);
+
+	// The packets are no longer received after leaving the group.
+	TEST_RES(sendto(sk_send, "hello", 6, 0, (struct sockaddr *)&group_addr,
+			sizeof(group_addr)),
+		 _ret == 6);
+	TEST_ERRNO(recv(sk_recv, buf, sizeof(buf), MSG_DONTWAIT), EAGAIN);
+}
+END_TEST()
+
+FN_SETUP(cleanup)
+{
+	CHECK(close(sk_recv));
+	CHECK(close(sk_send));
+}
+END_SETUP()
@@ -0,0 +1,179 @@
+// SPDX-License-Identifier: MPL-2.0
+
+#include <fcntl.h>
+#include <stdint.h>
+#include <string.h>
+#include <sys/ioctl.h>
+#include <sys/socket.h>
+#include <netinet/in.h>
+#include <arpa/inet.h>
+#include <unistd.h>
+
+#include "test.h"
+
+struct nf_rule {
+	uint32_t hook;
+	uint32_t verdict;
+	uint32_t ifindex;
+	uint16_t family;
+	uint8_t protocol;
+	uint8_t __pad;
+	uint8_t src_addr[16];
+	uint8_t dst_addr[16];
+	uint8_t src_prefix_len;
+	uint8_t dst_prefix_len;
+	uint16_t src_port;
+	uint16_t dst_port;
+	uint16_t __pad2;
+};
+
+#define NFADDRULE _IOW('N', 0, struct nf_rule)
+#define NFFLUSH _IO('N', 1)
+
+#define NF_INET_PRE_ROUTING 0
+#define NF_INET_LOCAL_IN 1
+#define NF_INET_LOCAL_OUT 3
+
+#define NF_DROP 0
+#define NF_ACCEPT 1
+
+#define PORT_A 0x1260
+#define PORT_B 0x1261
+
+static int nf_fd;
+static int sk_a;
+static int sk_b;
+static struct sockaddr_in addr_a;
+static struct sockaddr_in addr_b;
+
+FN_SETUP(open)
+{
+	nf_fd = CHECK(open("/dev/netfilter", O_RDWR));
+
+	sk_a = CHECK(socket(AF_INET, SOCK_DGRAM, 0));
+	sk_b = CHECK(socket(AF_INET, SOCK_DGRAM, 0));
+
+	addr_a.sin_family = AF_INET;
+	addr_a.sin_port = htons(PORT_A);
+	addr_a.sin_addr.s_addr = htonl(INADDR_LOOPBACK);
+	CHECK(bind(sk_a, (struct sockaddr *)&addr_a, sizeof(addr_a)));
+
+	addr_b.sin_family = AF_INET;
+	addr_b.sin_port = htons(PORT_B);
+	addr_b.sin_addr.s_addr = htonl(INADDR_LOOPBACK);
+	CHECK(bind(sk_b, (struct sockaddr *)&addr_b, sizeof(addr_b)));
+}
+END_SETUP()
+
+static struct nf_rule udp_rule(uint32_t hook, uint32_t verdict, int dst_port)
+{
+	struct nf_rule rule = { .hook = hook,
+				.verdict = verdict,
+				.family = AF_INET,
+				.protocol = IPPROTO_UDP,
+				.dst_port = htons(dst_port) };
+
+	return rule;
+}
+
+static int send_and_recv(int sk_from, struct sockaddr_in *to, int sk_to)
+{
+	char buf[6];
+
+	if (sendto(sk_from, "hello", 6, 0, (struct sockaddr *)to,
+		   sizeof(*to)) != 6)
+		return -1;
+
+	return recv(sk_to, buf, sizeof(buf), MSG_DONTWAIT);
+}
+
+FN_TEST(invalid_rules)
+{
+	struct nf_rule rule;
+
+	rule = udp_rule(2, NF_DROP, PORT_A);
+	TEST_ERRNO(ioctl(nf_fd, NFADDRULE, &rule), EINVAL);
+
+	rule = udp_rule(NF_INET_LOCAL_IN, 2, PORT_A);
+	TEST_ERRNO(ioctl(nf_fd, NFADDRULE, &rule), EINVAL);
+
+	rule = udp_rule(NF_INET_LOCAL_IN, NF_DROP, PORT_A);
+	rule.family = AF_UNIX;
+	TEST_ERRNO(ioctl(nf_fd, NFADDRULE, &rule), EAFNOSUPPORT);
+
+	rule = udp_rule(NF_INET_LOCAL_IN, NF_DROP, PORT_A);
+	rule.dst_prefix_len = 33;
+	TEST_ERRNO(ioctl(nf_fd, NFADDRULE, &rule), EINVAL);
+
+	rule = udp_rule(NF_INET_LOCAL_IN, NF_DROP, PORT_A);
+	rule.family = AF_UNSPEC;
+	rule.dst_prefix_len = 8;
+	TEST_ERRNO(ioctl(nf_fd, NFADDRULE, &rule), EINVAL);
+
+	rule = udp_rule(NF_INET_LOCAL_IN, NF_DROP, PORT_A);
+	rule.ifindex = 100;
+	TEST_ERRNO(ioctl(nf_fd, NFADDRULE, &rule), ENODEV);
+
+	TEST_ERRNO(ioctl(nf_fd, NFFLUSH, 2), EINVAL);
+}
+END_TEST()
+
+FN_TEST(input_drop)
+{
+	struct nf_rule rule = udp_rule(NF_INET_LOCAL_IN, NF_DROP, PORT_B);
+
+	TEST_RES(send_and_recv(sk_a, &addr_b, sk_b), _ret == 6);
+
+	TEST_SUCC(ioctl(nf_fd, NFADDRULE, &rule));
+	TEST_ERRNO(send_and_recv(sk_a, &addr_b, sk_b), EAGAIN);
+	// Packets to other ports are not affected.
+	TEST_RES(send_and_recv(sk_b, &addr_a, sk_a), _ret == 6);
+
+	TEST_SUCC(ioctl(nf_fd, NFFLUSH, NF_INET_LOCAL_IN));
+	TEST_RES(send_and_recv(sk_a, &addr_b, sk_b), _ret == 6);
+}
+END_TEST()
+
+FN_TEST(output_drop)
+{
+	struct nf_rule rule = udp_rule(NF_INET_LOCAL_OUT, NF_DROP, 0);
+
+	rule.dst_prefix_len = 8;
+	rule.dst_addr[0] = 127;
+	TEST_SUCC(ioctl(nf_fd, NFADDRULE, &rule));
+	TEST_ERRNO(send_and_recv(sk_a, &addr_b, sk_b), EAGAIN);
+	TEST_ERRNO(send_and_recv(sk_b, &addr_a, sk_a), EAGAIN);
+
+	// Flushing other hooks does not remove the rule.
+	TEST_SUCC(ioctl(nf_fd, NFFLUSH, NF_INET_PRE_ROUTING));
+	TEST_ERRNO(send_and_recv(sk_a, &addr_b, sk_b), EAGAIN);
+
+	TEST_SUCC(ioctl(nf_fd, NFFLUSH, NF_INET_LOCAL_OUT));
+	TEST_RES(send_and_recv(sk_a, &addr_b, sk_b), _ret == 6);
+}
+END_TEST()
+
+FN_TEST(first_match)
+{
+	struct nf_rule accept_rule =
+		udp_rule(NF_INET_PRE_ROUTING, NF_ACCEPT, PORT_B);
+	struct nf_rule drop_rule = udp_rule(NF_INET_PRE_ROUTING, NF_DROP, 0);
+
+	// The first matching rule wins.
+	TEST_SUCC(ioctl(nf_fd, NFADDRULE, &accept_rule));
+	TEST_SUCC(ioctl(nf_fd, NFADDRULE, &drop_rule));
+	TEST_RES(send_and_recv(sk_a, &addr_b, sk_b), _ret == 6);
+	TEST_ERRNO(send_and_recv(sk_b, &addr_a, sk_a), EAGAIN);
+
+	TEST_SUCC(ioctl(nf_fd, NFFLUSH, NF_INET_PRE_ROUTING));
+	TEST_RES(send_and_recv(sk_b, &addr_a, sk_a), _ret == 6);
+}
+END_TEST()
+
+FN_SETUP(cleanup)
+{
+	CHECK(close(sk_a));
+	CHECK(close(sk_b));
+	CHECK(close(nf_fd));
+}
+END_SETUP()
@@ -0,0 +1,294 @@
+// SPDX-License-Identifier: MPL-2.0
+
+#include <sys/socket.h>
+#include <linux/netlink.h>
+#include <linux/rtnetlink.h>
+#include <arpa/inet.h>
+#include <net/if.h>
+#include <net/if_arp.h>
+#include <unistd.h>
+
+#include "test.h"
+
+static int sk_route;
+static int lo_index;
+
+#define BUF_SIZE 8192
+static char buf[BUF_SIZE];
+
+FN_SETUP(socket)
+{
+	struct sockaddr_nl addr = { .nl_family = AF_NETLINK };
+
+	sk_route = CHECK(socket(AF_NETLINK, SOCK_RAW, NETLINK_ROUTE));
+	CHECK(bind(sk_route, (struct sockaddr *)&addr, sizeof(addr)));
+}
+END_SETUP()
+
+FN_TEST(invalid_socket)
+{
+	TEST_ERRNO(socket(AF_NETLINK, SOCK_STREAM, NETLINK_ROUTE),
+		   ESOCKTNOSUPPORT);
+	TEST_ERRNO(socket(AF_NETLINK, SOCK_RAW, 100), EPROTONOSUPPORT);
+}
+END_TEST()
+
+FN_TEST(getsockname)
+{
+	struct sockaddr_nl addr;
+	socklen_t addrlen = sizeof(addr);
+
+	TEST_RES(getsockname(sk_route, (struct sockaddr *)&addr, &addrlen),
+		 addrlen == sizeof(addr) && addr.nl_family == AF_NETLINK &&
+			 addr.nl_pid != 0 && addr.nl_groups == 0);
+}
+END_TEST()
+
+FN_TEST(bind_in_use)
+{
+	struct sockaddr_nl addr;
+	socklen_t addrlen = sizeof(addr);
+	int sk;
+
+	CHECK(getsockname(sk_route, (struct sockaddr *)&addr, &addrlen));
+
+	sk = TEST_SUCC(socket(AF_NETLINK, SOCK_DGRAM, NETLINK_ROUTE));
+	TEST_ERRNO(bind(sk, (struct sockaddr *)&addr, sizeof(addr)),
+		   EADDRINUSE);
+	TEST_SUCC(close(sk));
+}
+END_TEST()
+
+struct request {
+	struct nlmsghdr hdr;
+	union {
+		struct ifinfomsg ifinfo;
+		struct ifaddrmsg ifaddr;
+		struct rtmsg rt;
+	};
+	char attrs[64];
+};
+
+static int send_request(struct request *req, size_t payload_len, int type,
+			int flags, int seq)
+{
+	req->hdr.nlmsg_len = NLMSG_LENGTH(payload_len);
+	req->hdr.nlmsg_type = type;
+	req->hdr.nlmsg_flags = NLM_F_REQUEST | flags;
+	req->hdr.nlmsg_seq = seq;
+	req->hdr.nlmsg_pid = 0;
+
+	return send(sk_route, req, req->hdr.nlmsg_len, 0);
+}
+
+// Receives the replies of a dump request until `NLMSG_DONE` is received, and
+// returns the number of the messages (excluding `NLMSG_DONE`) of the type.
+static int recv_dump(int type, int seq, void (*fn)(struct nlmsghdr *))
+{
+	struct nlmsghdr *hdr;
+	int len, count = 0;
+
+	for (;;) {
+		len = recv(sk_route, buf, BUF_SIZE, 0);
+		if (len < 0)
+			return -1;
+
+		for (hdr = (struct nlmsghdr *)buf; NLMSG_OK(hdr, len);
+		     hdr = NLMSG_NEXT(hdr, len)) {
+			if (hdr->nlmsg_seq != seq)
+				return -1;
+			if (hdr->nlmsg_type == NLMSG_DONE)
+				return count;
+			if (hdr->nlmsg_type != type ||
+			    !(hdr->nlmsg_flags & NLM_F_MULTI))
+				return -1;
+
+			fn(hdr);
+			count++;
+		}
+	}
+}
+
+static void find_lo_link(struct nlmsghdr *hdr)
+{
+	struct ifinfomsg *ifinfo = NLMSG_DATA(hdr);
+	struct rtattr *attr = IFLA_RTA(ifinfo);
+	int attr_len = IFLA_PAYLOAD(hdr);
+
+	for (; RTA_OK(attr, attr_len); attr = RTA_NEXT(attr, attr_len)) {
+		if (attr->rta_type == IFLA_IFNAME &&
+		    strcmp(RTA_DATA(attr), "lo") == 0)
+			lo_index = ifinfo->ifi_index;
+	}
+}
+
+FN_TEST(dump_links)
+{
+	struct request req = {};
+
+	req.ifinfo.ifi_family = AF_UNSPEC;
+	TEST_RES(send_request(&req, sizeof(req.ifinfo), RTM_GETLINK,
+			      NLM_F_DUMP, 1),
+		 _ret == NLMSG_LENGTH(sizeof(req.ifinfo)));
+
+	TEST_RES(recv_dump(RTM_NEWLINK, 1, find_lo_link),
+		 _ret > 0 && lo_index > 0);
+}
+END_TEST()
+
+FN_TEST(get_link)
+{
+	struct request req = {};
+	struct nlmsghdr *hdr = (struct nlmsghdr *)buf;
+	struct ifinfomsg *ifinfo = NLMSG_DATA(hdr);
+
+	req.ifinfo.ifi_family = AF_UNSPEC;
+	req.ifinfo.ifi_index = lo_index;
+	TEST_SUCC(send_request(&req, sizeof(req.ifinfo), RTM_GETLINK, 0, 2));
+
+	TEST_RES(recv(sk_route, buf, BUF_SIZE, 0),
+		 NLMSG_OK(hdr, _ret) && hdr->nlmsg_type == RTM_NEWLINK &&
+			 hdr->nlmsg_seq == 2 &&
+			 ifinfo->ifi_index == lo_index &&
+			 ifinfo->ifi_type == ARPHRD_LOOPBACK &&
+			 (ifinfo->ifi_flags & IFF_LOOPBACK) &&
+			 (ifinfo->ifi_flags & IFF_UP));
+}
+END_TEST()
+
+FN_TEST(get_link_nonexistent)
+{
+	struct request req = {};
+	struct nlmsghdr *hdr = (struct nlmsghdr *)buf;
+	struct nlmsgerr *err = NLMSG_DATA(hdr);
+
+	req.ifinfo.ifi_family = AF_UNSPEC;
+	req.ifinfo.ifi_index = 12345;
+	TEST_SUCC(send_request(&req, sizeof(req.ifinfo), RTM_GETLINK, 0, 3));
+
+	TEST_RES(recv(sk_route, buf, BUF_SIZE, 0),
+		 NLMSG_OK(hdr, _ret) && hdr->nlmsg_type == NLMSG_ERROR &&
+			 hdr->nlmsg_seq == 3 && err->error == -ENODEV &&
+			 err->msg.nlmsg_type == RTM_GETLINK &&
+			 err->msg.nlmsg_seq == 3);
+}
+END_TEST()
+
+static int found_lo_addr;
+
+static void find_lo_addr(struct nlmsghdr *hdr)
+{
+	struct ifaddrmsg *ifaddr = NLMSG_DATA(hdr);
+	struct rtattr *attr = IFA_RTA(ifaddr);
+	int attr_len = IFA_PAYLOAD(hdr);
+
+	for (; RTA_OK(attr, attr_len); attr = RTA_NEXT(attr, attr_len)) {
+		if (attr->rta_type == IFA_LOCAL &&
+		    *(in_addr_t *)RTA_DATA(attr) == htonl(INADDR_LOOPBACK) &&
+		    ifaddr->ifa_prefixlen == 8 &&
+		    ifaddr->ifa_index == lo_index)
+			found_lo_addr = 1;
+	}
+}
+
+FN_TEST(dump_addrs)
+{
+	struct request req = {};
+
+	req.ifaddr.ifa_family = AF_INET;
+	TEST_SUCC(send_request(&req, sizeof(req.ifaddr), RTM_GETADDR,
+			       NLM_F_DUMP, 4));
+
+	TEST_RES(recv_dump(RTM_NEWADDR, 4, find_lo_addr),
+		 _ret > 0 && found_lo_addr);
+}
+END_TEST()
+
+FN_TEST(new_addr_exists)
+{
+	struct request req = {};
+	struct nlmsghdr *hdr = (struct nlmsghdr *)buf;
+	struct nlmsgerr *err = NLMSG_DATA(hdr);
+	struct rtattr *attr = IFA_RTA(&req.ifaddr);
+
+	req.ifaddr.ifa_family = AF_INET;
+	req.ifaddr.ifa_prefixlen = 8;
+	req.ifaddr.ifa_index = lo_index;
+	attr->rta_type = IFA_LOCAL;
+	attr->rta_len = RTA_LENGTH(sizeof(in_addr_t));
+	*(in_addr_t *)RTA_DATA(attr) = htonl(INADDR_LOOPBACK);
+	TEST_SUCC(send_request(&req, sizeof(req.ifaddr) + attr->rta_len,
+			       RTM_NEWADDR,
+			       NLM_F_ACK | NLM_F_CREATE | NLM_F_EXCL, 5));
+
+	TEST_RES(recv(sk_route, buf, BUF_SIZE, 0),
+		 NLMSG_OK(hdr, _ret) && hdr->nlmsg_type == NLMSG_ERROR &&
+			 hdr->nlmsg_seq == 5 && err->error == -EEXIST);
+}
+END_TEST()
+
+FN_TEST(ack)
+{
+	struct request req = {};
+	struct nlmsghdr *hdr = (struct nlmsghdr *)buf;
+	struct nlmsgerr *err = NLMSG_DATA(hdr);
+
+	// Control messages are not handled, but they can still be acknowledged.
+	TEST_SUCC(send_request(&req, 0, NLMSG_NOOP, NLM_F_ACK, 6));
+
+	TEST_RES(recv(sk_route, buf, BUF_SIZE, 0),
+		 NLMSG_OK(hdr, _ret) && hdr->nlmsg_type == NLMSG_ERROR &&
+			 hdr->nlmsg_seq == 6 && err->error == 0 &&
+			 err->msg.nlmsg_type == NLMSG_NOOP);
+
+	TEST_ERRNO(recv(sk_route, buf, BUF_SIZE, MSG_DONTWAIT), EAGAIN);
+}
+END_TEST()
+
+FN_TEST(unsupported_type)
+{
+	struct request req = {};
+	struct nlmsghdr *hdr = (struct nlmsghdr *)buf;
+	struct nlmsgerr *err = NLMSG_DATA(hdr);
+
+	TEST_SUCC(send_request(&req, 0, 0x1000, 0, 7));
+
+	TEST_RES(recv(sk_route, buf, BUF_SIZE, 0),
+		 NLMSG_OK(hdr, _ret) && hdr->nlmsg_type == NLMSG_ERROR &&
+			 hdr->nlmsg_seq == 7 && err->error == -EOPNOTSUPP);
+}
+END_TEST()
+
+FN_TEST(peek_and_trunc)
+{
+	struct request req = {};
+	int len;
+
+	req.ifinfo.ifi_family = AF_UNSPEC;
+	req.ifinfo.ifi_index = lo_index;
+	TEST_SUCC(send_request(&req, sizeof(req.ifinfo), RTM_GETLINK, 0, 8));
+
+	len = TEST_SUCC(recv(sk_route, buf, BUF_SIZE, MSG_PEEK | MSG_TRUNC));
+	TEST_RES(recv(sk_route, buf, 1, MSG_TRUNC), _ret == len);
+	TEST_ERRNO(recv(sk_route, buf, BUF_SIZE, MSG_DONTWAIT), EAGAIN);
+}
+END_TEST()
+
+FN_TEST(send_to_user)
+{
+	struct request req = {};
+	struct sockaddr_nl addr = { .nl_family = AF_NETLINK, .nl_pid = 12345 };
+
+	req.hdr.nlmsg_len = NLMSG_LENGTH(0);
+	req.hdr.nlmsg_type = NLMSG_NOOP;
+	TEST_ERRNO(sendto(sk_route, &req, req.hdr.nlmsg_len, 0,
+			  (struct sockaddr *)&addr, sizeof(addr)),
+		   ECONNREFUSED);
+}
+END_TEST()
+
+FN_SETUP(cleanup)
+{
+	CHECK(close(sk_route));
+}
+END_SETUP()
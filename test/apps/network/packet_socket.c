@@ -0,0 +1,145 @@
+// SPDX-License-Identifier: MPL-2.0
+
+#include <sys/socket.h>
+#include <linux/filter.h>
+#include <linux/if_ether.h>
+#include <linux/if_packet.h>
+#include <arpa/inet.h>
+#include <net/if_arp.h>
+#include <unistd.h>
+
+#include "test.h"
+
+#define MAX_IFINDEX 16
+
+static int sk_raw;
+static int sk_dgram;
+static int lo_index;
+
+FN_SETUP(socket)
+{
+	sk_raw = CHECK(socket(AF_PACKET, SOCK_RAW, htons(ETH_P_ALL)));
+	sk_dgram = CHECK(socket(AF_PACKET, SOCK_DGRAM, 0));
+}
+END_SETUP()
+
+FN_TEST(invalid_socket)
+{
+	TEST_ERRNO(socket(AF_PACKET, SOCK_STREAM, htons(ETH_P_ALL)),
+		   ESOCKTNOSUPPORT);
+}
+END_TEST()
+
+FN_TEST(getsockname_unbound)
+{
+	struct sockaddr_ll addr;
+	socklen_t addrlen = sizeof(addr);
+
+	TEST_RES(getsockname(sk_raw, (struct sockaddr *)&addr, &addrlen),
+		 addr.sll_family == AF_PACKET &&
+			 addr.sll_protocol == htons(ETH_P_ALL) &&
+			 addr.sll_ifindex == 0);
+
+	addrlen = sizeof(addr);
+	TEST_RES(getsockname(sk_dgram, (struct sockaddr *)&addr, &addrlen),
+		 addr.sll_family == AF_PACKET && addr.sll_protocol == 0 &&
+			 addr.sll_ifindex == 0);
+}
+END_TEST()
+
+FN_TEST(bind_lo)
+{
+	struct sockaddr_ll addr;
+	socklen_t addrlen;
+	int i;
+
+	// The protocol is not changed if a zero protocol is specified.
+	for (i = 1; i <= MAX_IFINDEX; ++i) {
+		addr = (struct sockaddr_ll){ .sll_family = AF_PACKET,
+					     .sll_ifindex = i };
+		if (bind(sk_raw, (struct sockaddr *)&addr, sizeof(addr)) < 0)
+			continue;
+
+		addrlen = sizeof(addr);
+		CHECK(getsockname(sk_raw, (struct sockaddr *)&addr, &addrlen));
+		if (addr.sll_hatype == ARPHRD_LOOPBACK) {
+			lo_index = i;
+			break;
+		}
+	}
+
+	TEST_RES(lo_index, _ret > 0 && addr.sll_family == AF_PACKET &&
+				   addr.sll_protocol == htons(ETH_P_ALL) &&
+				   addr.sll_ifindex == lo_index &&
+				   addr.sll_halen == ETH_ALEN);
+}
+END_TEST()
+
+FN_TEST(bind_invalid)
+{
+	struct sockaddr_ll addr = { .sll_family = AF_PACKET,
+				    .sll_ifindex = 12345 };
+
+	TEST_ERRNO(bind(sk_dgram, (struct sockaddr *)&addr, sizeof(addr)),
+		   ENODEV);
+	TEST_ERRNO(bind(sk_dgram, (struct sockaddr *)&addr, 4), EINVAL);
+}
+END_TEST()
+
+FN_TEST(send_without_iface)
+{
+	char buf[ETH_HLEN] = {};
+
+	TEST_ERRNO(send(sk_dgram, buf, sizeof(buf), 0), ENXIO);
+}
+END_TEST()
+
+FN_TEST(filter)
+{
+	struct sock_filter drop_all[] = {
+		BPF_STMT(BPF_RET | BPF_K, 0),
+	};
+	struct sock_filter no_ret[] = {
+		BPF_STMT(BPF_LD | BPF_W | BPF_ABS, 0),
+	};
+	struct sock_filter bad_jump[] = {
+		BPF_JUMP(BPF_JMP | BPF_JEQ | BPF_K, 0, 5, 0),
+		BPF_STMT(BPF_RET | BPF_K, 0),
+	};
+	struct sock_fprog prog;
+	int zero = 0;
+
+	prog = (struct sock_fprog){ .len = 0, .filter = drop_all };
+	TEST_ERRNO(setsockopt(sk_raw, SOL_SOCKET, SO_ATTACH_FILTER, &prog,
+			      sizeof(prog)),
+		   EINVAL);
+	prog = (struct sock_fprog){ .len = 1, .filter = no_ret };
+	TEST_ERRNO(setsockopt(sk_raw, SOL_SOCKET, SO_ATTACH_FILTER, &prog,
+			      sizeof(prog)),
+		   EINVAL);
+	prog = (struct sock_fprog){ .len = 2, .filter = bad_jump };
+	TEST_ERRNO(setsockopt(sk_raw, SOL_SOCKET, SO_ATTACH_FILTER, &prog,
+			      sizeof(prog)),
+		   EINVAL);
+
+	TEST_ERRNO(setsockopt(sk_raw, SOL_SOCKET, SO_DETACH_FILTER, &zero,
+			      sizeof(zero)),
+		   ENOENT);
+
+	prog = (struct sock_fprog){ .len = 1, .filter = drop_all };
+	TEST_SUCC(setsockopt(sk_raw, SOL_SOCKET, SO_ATTACH_FILTER, &prog,
+			     sizeof(prog)));
+	TEST_SUCC(setsockopt(sk_raw, SOL_SOCKET, SO_DETACH_FILTER, &zero,
+			      sizeof(zero)));
+	TEST_ERRNO(setsockopt(sk_raw, SOL_SOCKET, SO_DETACH_FILTER, &zero,
+			      sizeof(zero)),
+		   ENOENT);
+}
+END_TEST()
+
+FN_SETUP(cleanup)
+{
+	CHECK(close(sk_raw));
+	CHECK(close(sk_dgram));
+}
+END_SETUP()
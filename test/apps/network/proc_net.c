@@ -0,0 +1,202 @@
+// SPDX-License-Identifier: MPL-2.0
+
+#include <fcntl.h>
+#include <stdio.h>
+#include <stdlib.h>
+#include <string.h>
+#include <sys/socket.h>
+#include <netinet/in.h>
+#include <arpa/inet.h>
+#include <unistd.h>
+
+#include "test.h"
+
+#define TCP_PORT 0x1290
+#define UDP_PORT 0x1291
+
+static char buf[16384];
+
+static int read_file(const char *path)
+{
+	int fd, len;
+
+	fd = open(path, O_RDONLY);
+	if (fd < 0)
+		return -1;
+
+	len = read(fd, buf, sizeof(buf) - 1);
+	close(fd);
+	if (len < 0)
+		return -1;
+
+	buf[len] = 0;
+	return len;
+}
+
+// Returns whether a socket with the local address, the remote address, and
+// the state is listed in the file read to `buf`.
+static int find_socket(const char *local, const char *remote, const char *state)
+{
+	char pattern[64];
+
+	snprintf(pattern, sizeof(pattern), ": %s %s %s ", local, remote,
+		 state);
+	return strstr(buf, pattern) != NULL;
+}
+
+// Returns the value of the field in the `/proc/net/snmp` file read to `buf`,
+// or -1 if the field does not exist.
+static long snmp_field(const char *protocol, const char *field)
+{
+	char prefix[16];
+	char *names, *values, *name, *value;
+	char *names_end, *values_end;
+
+	snprintf(prefix, sizeof(prefix), "%s:", protocol);
+
+	names = strstr(buf, prefix);
+	if (names == NULL)
+		return -1;
+	names_end = strchr(names, '\n');
+	if (names_end == NULL)
+		return -1;
+	values = names_end + 1;
+	if (strncmp(values, prefix, strlen(prefix)) != 0)
+		return -1;
+	values_end = strchr(values, '\n');
+	if (values_end == NULL)
+		return -1;
+	*names_end = 0;
+	*values_end = 0;
+
+	name = strtok_r(names + strlen(prefix), " ", &names);
+	value = strtok_r(values + strlen(prefix), " ", &values);
+	while (name != NULL && value != NULL) {
+		if (strcmp(name, field) == 0)
+			return atol(value);
+		name = strtok_r(NULL, " ", &names);
+		value = strtok_r(NULL, " ", &values);
+	}
+
+	return -1;
+}
+
+static long read_snmp_field(const char *protocol, const char *field)
+{
+	if (read_file("/proc/net/snmp") < 0)
+		return -1;
+	return snmp_field(protocol, field);
+}
+
+FN_TEST(tcp)
+{
+	struct sockaddr_in addr = { .sin_family = AF_INET,
+				    .sin_port = htons(TCP_PORT) };
+	socklen_t addr_len;
+	char client[16];
+	int sk_listen, sk_connect, sk_accept;
+
+	addr.sin_addr.s_addr = htonl(INADDR_LOOPBACK);
+
+	sk_listen = TEST_SUCC(socket(AF_INET, SOCK_STREAM, 0));
+	TEST_SUCC(bind(sk_listen, (struct sockaddr *)&addr, sizeof(addr)));
+	TEST_SUCC(listen(sk_listen, 2));
+
+	TEST_RES(read_file("/proc/net/tcp"),
+		 strncmp(buf, "  sl  local_address rem_address   st", 36) ==
+				 0 &&
+			 find_socket("0100007F:1290", "00000000:0000", "0A"));
+
+	sk_connect = TEST_SUCC(socket(AF_INET, SOCK_STREAM, 0));
+	TEST_SUCC(connect(sk_connect, (struct sockaddr *)&addr, sizeof(addr)));
+	sk_accept = TEST_SUCC(accept(sk_listen, NULL, NULL));
+
+	addr_len = sizeof(addr);
+	TEST_SUCC(getsockname(sk_connect, (struct sockaddr *)&addr, &addr_len));
+	snprintf(client, sizeof(client), "0100007F:%04X",
+		 ntohs(addr.sin_port));
+
+	TEST_RES(read_file("/proc/net/tcp"),
+		 find_socket("0100007F:1290", "00000000:0000", "0A") &&
+			 find_socket("0100007F:1290", client, "01") &&
+			 find_socket(client, "0100007F:1290", "01"));
+
+	TEST_SUCC(close(sk_accept));
+	TEST_SUCC(close(sk_connect));
+	TEST_SUCC(close(sk_listen));
+}
+END_TEST()
+
+FN_TEST(udp)
+{
+	struct sockaddr_in addr = { .sin_family = AF_INET,
+				    .sin_port = htons(UDP_PORT) };
+	int sk;
+
+	addr.sin_addr.s_addr = htonl(INADDR_LOOPBACK);
+
+	sk = TEST_SUCC(socket(AF_INET, SOCK_DGRAM, 0));
+	TEST_SUCC(bind(sk, (struct sockaddr *)&addr, sizeof(addr)));
+
+	TEST_RES(read_file("/proc/net/udp"),
+		 strncmp(buf, "   sl  local_address rem_address   st", 37) ==
+				 0 &&
+			 find_socket("0100007F:1291", "00000000:0000", "07"));
+
+	TEST_SUCC(close(sk));
+
+	TEST_RES(read_file("/proc/net/udp"),
+		 !find_socket("0100007F:1291", "00000000:0000", "07"));
+}
+END_TEST()
+
+FN_TEST(snmp)
+{
+	struct sockaddr_in addr = { .sin_family = AF_INET,
+				    .sin_port = htons(UDP_PORT) };
+	long active_opens, out_datagrams, in_datagrams;
+	int sk_listen, sk_connect, sk_recv, sk_send;
+
+	addr.sin_addr.s_addr = htonl(INADDR_LOOPBACK);
+
+	TEST_RES(read_snmp_field("Tcp", "RtoAlgorithm"), _ret == 1);
+	TEST_RES(read_snmp_field("Tcp", "MaxConn"), _ret == -1);
+
+	// Establishing a TCP connection increases `ActiveOpens`.
+	active_opens = TEST_SUCC(read_snmp_field("Tcp", "ActiveOpens"));
+
+	addr.sin_port = htons(TCP_PORT);
+	sk_listen = TEST_SUCC(socket(AF_INET, SOCK_STREAM, 0));
+	TEST_SUCC(bind(sk_listen, (struct sockaddr *)&addr, sizeof(addr)));
+	TEST_SUCC(listen(sk_listen, 2));
+	sk_connect = TEST_SUCC(socket(AF_INET, SOCK_STREAM, 0));
+	TEST_SUCC(connect(sk_connect, (struct sockaddr *)&addr, sizeof(addr)));
+
+	TEST_RES(read_snmp_field("Tcp", "ActiveOpens"),
+		 _ret == active_opens + 1);
+
+	TEST_SUCC(close(sk_connect));
+	TEST_SUCC(close(sk_listen));
+
+	// Sending and receiving a UDP datagram increases `OutDatagrams` and
+	// `InDatagrams`.
+	out_datagrams = TEST_SUCC(read_snmp_field("Udp", "OutDatagrams"));
+	in_datagrams = TEST_SUCC(read_snmp_field("Udp", "InDatagrams"));
+
+	addr.sin_port = htons(UDP_PORT);
+	sk_recv = TEST_SUCC(socket(AF_INET, SOCK_DGRAM, 0));
+	TEST_SUCC(bind(sk_recv, (struct sockaddr *)&addr, sizeof(addr)));
+	sk_send = TEST_SUCC(socket(AF_INET, SOCK_DGRAM, 0));
+	TEST_RES(sendto(sk_send, "hello", 5, 0, (struct sockaddr *)&addr,
+			sizeof(addr)),
+		 _ret == 5);
+	TEST_RES(recv(sk_recv, buf, sizeof(buf), 0), _ret == 5);
+
+	TEST_RES(read_snmp_field("Udp", "OutDatagrams"),
+		 _ret > out_datagrams);
+	TEST_RES(read_snmp_field("Udp", "InDatagrams"), _ret > in_datagrams);
+
+	TEST_SUCC(close(sk_send));
+	TEST_SUCC(close(sk_recv));
+}
+END_TEST()
@@ -0,0 +1,227 @@
+// SPDX-License-Identifier: MPL-2.0
+
+#include <string.h>
+#include <sys/socket.h>
+#include <netinet/in.h>
+#include <netinet/ip.h>
+#include <linux/icmp.h>
+#include <netinet/icmp6.h>
+#include <arpa/inet.h>
+#include <unistd.h>
+
+#include "test.h"
+
+static int sk_ping;
+static int sk_ping6;
+static int sk_raw;
+static int sk_raw_udp;
+static struct sockaddr_in lo_addr;
+static struct sockaddr_in6 lo_addr6;
+
+FN_SETUP(socket)
+{
+	sk_ping = CHECK(socket(AF_INET, SOCK_DGRAM, IPPROTO_ICMP));
+	sk_ping6 = CHECK(socket(AF_INET6, SOCK_DGRAM, IPPROTO_ICMPV6));
+	sk_raw = CHECK(socket(AF_INET, SOCK_RAW, IPPROTO_ICMP));
+	sk_raw_udp = CHECK(socket(AF_INET, SOCK_RAW, IPPROTO_UDP));
+
+	lo_addr.sin_family = AF_INET;
+	lo_addr.sin_addr.s_addr = htonl(INADDR_LOOPBACK);
+
+	lo_addr6.sin6_family = AF_INET6;
+	lo_addr6.sin6_addr = in6addr_loopback;
+}
+END_SETUP()
+
+FN_TEST(invalid_socket)
+{
+	TEST_ERRNO(socket(AF_INET, SOCK_RAW, 0), EPROTONOSUPPORT);
+	TEST_ERRNO(socket(AF_INET6, SOCK_RAW, 0), EPROTONOSUPPORT);
+}
+END_TEST()
+
+FN_TEST(getsockname)
+{
+	struct sockaddr_in addr;
+	socklen_t addrlen = sizeof(addr);
+
+	// The port of raw sockets is the protocol.
+	TEST_RES(getsockname(sk_raw_udp, (struct sockaddr *)&addr, &addrlen),
+		 addrlen == sizeof(addr) && addr.sin_family == AF_INET &&
+			 addr.sin_port == htons(IPPROTO_UDP) &&
+			 addr.sin_addr.s_addr == htonl(INADDR_ANY));
+}
+END_TEST()
+
+FN_TEST(icmp_filter)
+{
+	struct icmp_filter filter;
+	socklen_t optlen = sizeof(filter);
+
+	TEST_RES(getsockopt(sk_raw, SOL_RAW, ICMP_FILTER, &filter, &optlen),
+		 optlen == sizeof(filter) && filter.data == 0);
+
+	TEST_ERRNO(getsockopt(sk_raw_udp, SOL_RAW, ICMP_FILTER, &filter,
+			      &optlen),
+		   EOPNOTSUPP);
+	TEST_ERRNO(getsockopt(sk_ping, SOL_RAW, ICMP_FILTER, &filter, &optlen),
+		   EOPNOTSUPP);
+
+	// Block the echo requests, so only the echo replies are received later.
+	filter.data = 1 << ICMP_ECHO;
+	TEST_SUCC(setsockopt(sk_raw, SOL_RAW, ICMP_FILTER, &filter,
+			     sizeof(filter)));
+	TEST_RES(getsockopt(sk_raw, SOL_RAW, ICMP_FILTER, &filter, &optlen),
+		 optlen == sizeof(filter) && filter.data == 1 << ICMP_ECHO);
+}
+END_TEST()
+
+FN_TEST(ip_ttl)
+{
+	int ttl;
+	socklen_t optlen = sizeof(ttl);
+
+	TEST_RES(getsockopt(sk_ping, SOL_IP, IP_TTL, &ttl, &optlen),
+		 optlen == sizeof(ttl) && ttl == 64);
+
+	ttl = 0;
+	TEST_ERRNO(setsockopt(sk_ping, SOL_IP, IP_TTL, &ttl, sizeof(ttl)),
+		   EINVAL);
+	ttl = 256;
+	TEST_ERRNO(setsockopt(sk_ping, SOL_IP, IP_TTL, &ttl, sizeof(ttl)),
+		   EINVAL);
+
+	ttl = 5;
+	TEST_SUCC(setsockopt(sk_ping, SOL_IP, IP_TTL, &ttl, sizeof(ttl)));
+	TEST_RES(getsockopt(sk_ping, SOL_IP, IP_TTL, &ttl, &optlen),
+		 optlen == sizeof(ttl) && ttl == 5);
+
+	// -1 restores the default TTL.
+	ttl = -1;
+	TEST_SUCC(setsockopt(sk_ping, SOL_IP, IP_TTL, &ttl, sizeof(ttl)));
+	TEST_RES(getsockopt(sk_ping, SOL_IP, IP_TTL, &ttl, &optlen),
+		 optlen == sizeof(ttl) && ttl == 64);
+}
+END_TEST()
+
+FN_TEST(ping_invalid)
+{
+	struct icmphdr icmp = { .type = ICMP_ECHOREPLY };
+
+	TEST_ERRNO(sendto(sk_ping, &icmp, sizeof(icmp), 0,
+			  (struct sockaddr *)&lo_addr, sizeof(lo_addr)),
+		   EINVAL);
+
+	icmp.type = ICMP_ECHO;
+	TEST_ERRNO(sendto(sk_ping, &icmp, sizeof(icmp) - 1, 0,
+			  (struct sockaddr *)&lo_addr, sizeof(lo_addr)),
+		   EINVAL);
+}
+END_TEST()
+
+FN_TEST(ping_loopback)
+{
+	struct {
+		struct icmphdr icmp;
+		char data[6];
+	} request = { .icmp = { .type = ICMP_ECHO,
+				.un.echo.sequence = htons(1) },
+		      .data = "hello" },
+	  reply;
+	struct {
+		struct iphdr ip;
+		struct icmphdr icmp;
+		char data[6];
+	} raw_reply;
+	struct sockaddr_in addr;
+	socklen_t addrlen = sizeof(addr);
+	char cmsg_buf[CMSG_SPACE(sizeof(int))];
+	struct iovec iov = { .iov_base = &reply, .iov_len = sizeof(reply) };
+	struct msghdr msg = { .msg_iov = &iov,
+			      .msg_iovlen = 1,
+			      .msg_control = cmsg_buf,
+			      .msg_controllen = sizeof(cmsg_buf) };
+	struct cmsghdr *cmsg;
+	int one = 1;
+	unsigned short ident;
+
+	TEST_SUCC(setsockopt(sk_ping, SOL_IP, IP_RECVTTL, &one, sizeof(one)));
+
+	TEST_RES(sendto(sk_ping, &request, sizeof(request), 0,
+			(struct sockaddr *)&lo_addr, sizeof(lo_addr)),
+		 _ret == sizeof(request));
+
+	// The identifier is allocated by the kernel.
+	TEST_RES(getsockname(sk_ping, (struct sockaddr *)&addr, &addrlen),
+		 addrlen == sizeof(addr) && addr.sin_family == AF_INET &&
+			 addr.sin_port != 0);
+	ident = addr.sin_port;
+
+	TEST_RES(recvmsg(sk_ping, &msg, 0),
+		 _ret == sizeof(reply) && reply.icmp.type == ICMP_ECHOREPLY &&
+			 reply.icmp.un.echo.id == ident &&
+			 reply.icmp.un.echo.sequence == htons(1) &&
+			 strcmp(reply.data, "hello") == 0);
+
+	cmsg = CMSG_FIRSTHDR(&msg);
+	TEST_RES(cmsg != NULL, cmsg->cmsg_level == SOL_IP &&
+				       cmsg->cmsg_type == IP_TTL &&
+				       *(int *)CMSG_DATA(cmsg) == 64);
+
+	// The raw socket receives the echo reply along with the IP header.
+	addrlen = sizeof(addr);
+	TEST_RES(recvfrom(sk_raw, &raw_reply, sizeof(raw_reply), MSG_DONTWAIT,
+			  (struct sockaddr *)&addr, &addrlen),
+		 _ret == sizeof(raw_reply) && raw_reply.ip.version == 4 &&
+			 raw_reply.ip.protocol == IPPROTO_ICMP &&
+			 raw_reply.icmp.type == ICMP_ECHOREPLY &&
+			 raw_reply.icmp.un.echo.id == ident &&
+			 addr.sin_family == AF_INET &&
+			 addr.sin_addr.s_addr == htonl(INADDR_LOOPBACK));
+
+	// The echo request is blocked by the filter.
+	TEST_ERRNO(recv(sk_raw, &raw_reply, sizeof(raw_reply), MSG_DONTWAIT),
+		   EAGAIN);
+	TEST_ERRNO(recv(sk_ping, &reply, sizeof(reply), MSG_DONTWAIT), EAGAIN);
+}
+END_TEST()
+
+FN_TEST(ping6_loopback)
+{
+	struct {
+		struct icmp6_hdr icmp;
+		char data[6];
+	} request = { .icmp = { .icmp6_type = ICMP6_ECHO_REQUEST },
+		      .data = "world" },
+	  reply;
+	struct sockaddr_in6 addr;
+	socklen_t addrlen = sizeof(addr);
+	int hops;
+	socklen_t optlen = sizeof(hops);
+
+	TEST_RES(getsockopt(sk_ping6, SOL_IPV6, IPV6_UNICAST_HOPS, &hops,
+			    &optlen),
+		 optlen == sizeof(hops) && hops == 64);
+
+	TEST_RES(sendto(sk_ping6, &request, sizeof(request), 0,
+			(struct sockaddr *)&lo_addr6, sizeof(lo_addr6)),
+		 _ret == sizeof(request));
+
+	TEST_RES(recvfrom(sk_ping6, &reply, sizeof(reply), 0,
+			  (struct sockaddr *)&addr, &addrlen),
+		 _ret == sizeof(reply) &&
+			 reply.icmp.icmp6_type == ICMP6_ECHO_REPLY &&
+			 strcmp(reply.data, "world") == 0 &&
+			 addr.sin6_family == AF_INET6 &&
+			 IN6_IS_ADDR_LOOPBACK(&addr.sin6_addr));
+}
+END_TEST()
+
+FN_SETUP(cleanup)
+{
+	CHECK(close(sk_ping));
+	CHECK(close(sk_ping6));
+	CHECK(close(sk_raw));
+	CHECK(close(sk_raw_udp));
+}
+END_SETUP()
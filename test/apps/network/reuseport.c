@@ -0,0 +1,117 @@
+// SPDX-License-Identifier: MPL-2.0
+
+#include <fcntl.h>
+#include <sys/socket.h>
+#include <netinet/in.h>
+#include <arpa/inet.h>
+#include <unistd.h>
+
+#include "test.h"
+
+#define PORT 0x1280
+#define NUM_CLIENTS 32
+
+static struct sockaddr_in listen_addr;
+
+static int new_listener(int reuse_port)
+{
+	int sk;
+
+	sk = socket(AF_INET, SOCK_STREAM | SOCK_NONBLOCK, 0);
+	if (sk < 0)
+		return -1;
+
+	if (setsockopt(sk, SOL_SOCKET, SO_REUSEPORT, &reuse_port,
+		       sizeof(reuse_port)) < 0 ||
+	    bind(sk, (struct sockaddr *)&listen_addr, sizeof(listen_addr)) <
+		    0 ||
+	    listen(sk, NUM_CLIENTS) < 0) {
+		close(sk);
+		return -1;
+	}
+
+	return sk;
+}
+
+// Connects the clients and returns the number of the connections accepted by
+// `sk_a`, or -1 if not all the connections are accepted.
+static int connect_and_accept(int sk_a, int sk_b)
+{
+	int clients[NUM_CLIENTS];
+	int num_a = 0, num_b = 0;
+	int i, sk;
+
+	for (i = 0; i < NUM_CLIENTS; ++i) {
+		clients[i] = socket(AF_INET, SOCK_STREAM, 0);
+		if (clients[i] < 0 ||
+		    connect(clients[i], (struct sockaddr *)&listen_addr,
+			    sizeof(listen_addr)) < 0)
+			return -1;
+	}
+
+	while ((sk = accept(sk_a, NULL, NULL)) >= 0) {
+		close(sk);
+		++num_a;
+	}
+	while (sk_b >= 0 && (sk = accept(sk_b, NULL, NULL)) >= 0) {
+		close(sk);
+		++num_b;
+	}
+
+	for (i = 0; i < NUM_CLIENTS; ++i)
+		close(clients[i]);
+
+	if (num_a + num_b != NUM_CLIENTS)
+		return -1;
+	return num_a;
+}
+
+FN_SETUP(addr)
+{
+	listen_addr.sin_family = AF_INET;
+	listen_addr.sin_port = htons(PORT);
+	listen_addr.sin_addr.s_addr = htonl(INADDR_LOOPBACK);
+}
+END_SETUP()
+
+FN_TEST(without_reuseport)
+{
+	int sk_a, sk_b;
+
+	sk_a = TEST_SUCC(new_listener(0));
+
+	sk_b = TEST_SUCC(socket(AF_INET, SOCK_STREAM, 0));
+	TEST_ERRNO(bind(sk_b, (struct sockaddr *)&listen_addr,
+			sizeof(listen_addr)),
+		   EADDRINUSE);
+	TEST_SUCC(close(sk_b));
+
+	// Enabling `SO_REUSEPORT` on the new socket only is not enough.
+	TEST_ERRNO(new_listener(1), EADDRINUSE);
+
+	TEST_SUCC(close(sk_a));
+}
+END_TEST()
+
+FN_TEST(load_balance)
+{
+	int sk_a, sk_b;
+
+	sk_a = TEST_SUCC(new_listener(1));
+	sk_b = TEST_SUCC(new_listener(1));
+
+	// The connections are distributed to both listeners.
+	TEST_RES(connect_and_accept(sk_a, sk_b),
+		 _ret > 0 && _ret < NUM_CLIENTS);
+
+	// A socket without `SO_REUSEPORT` cannot join the group.
+	TEST_ERRNO(new_listener(0), EADDRINUSE);
+
+	// After one listener is closed, the other one accepts all the
+	// connections.
+	TEST_SUCC(close(sk_b));
+	TEST_RES(connect_and_accept(sk_a, -1), _ret == NUM_CLIENTS);
+
+	TEST_SUCC(close(sk_a));
+}
+END_TEST()
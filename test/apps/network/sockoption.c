@@ -2,6 +2,7 @@
 
 #include <stdio.h>
 #include <stdlib.h>
+#include <string.h>
 #include <sys/socket.h>
 #include <netinet/in.h>
 #include <netinet/tcp.h>
@@ -273,4 +274,101 @@ FN_TEST(keepidle)
 			    &keepidle_len),
 		 keepidle == 200);
 }
-END_TEST()
\ No newline at end of file
+END_TEST()
+FN_TEST(keepintvl_keepcnt)
+{
+	int value;
+	socklen_t value_len = sizeof(value);
+
+	// 1. Check default values
+	refresh_connection();
+	TEST_RES(getsockopt(sk_connected, IPPROTO_TCP, TCP_KEEPINTVL, &value,
+			    &value_len),
+		 value == 75);
+	TEST_RES(getsockopt(sk_connected, IPPROTO_TCP, TCP_KEEPCNT, &value,
+			    &value_len),
+		 value == 9);
+
+	// 2. Set and get values
+	value = 10;
+	TEST_SUCC(setsockopt(sk_connected, IPPROTO_TCP, TCP_KEEPINTVL, &value,
+			     sizeof(value)));
+	value = 3;
+	TEST_SUCC(setsockopt(sk_connected, IPPROTO_TCP, TCP_KEEPCNT, &value,
+			     sizeof(value)));
+	TEST_RES(getsockopt(sk_connected, IPPROTO_TCP, TCP_KEEPINTVL, &value,
+			    &value_len),
+		 value == 10);
+	TEST_RES(getsockopt(sk_connected, IPPROTO_TCP, TCP_KEEPCNT, &value,
+			    &value_len),
+		 value == 3);
+
+	// 3. Set invalid values
+	value = 0;
+	TEST_ERRNO(setsockopt(sk_connected, IPPROTO_TCP, TCP_KEEPINTVL, &value,
+			      sizeof(value)),
+		   EINVAL);
+	TEST_ERRNO(setsockopt(sk_connected, IPPROTO_TCP, TCP_KEEPCNT, &value,
+			      sizeof(value)),
+		   EINVAL);
+	value = 32768;
+	TEST_ERRNO(setsockopt(sk_connected, IPPROTO_TCP, TCP_KEEPINTVL, &value,
+			      sizeof(value)),
+		   EINVAL);
+	value = 128;
+	TEST_ERRNO(setsockopt(sk_connected, IPPROTO_TCP, TCP_KEEPCNT, &value,
+			      sizeof(value)),
+		   EINVAL);
+
+	// 4. Enable keepalive with the new values
+	value = 1;
+	TEST_SUCC(setsockopt(sk_connected, SOL_SOCKET, SO_KEEPALIVE, &value,
+			     sizeof(value)));
+}
+END_TEST()
+
+FN_TEST(congestion)
+{
+	char name[16];
+	socklen_t name_len;
+
+	TEST_SUCC(setsockopt(sk_listen, IPPROTO_TCP, TCP_CONGESTION, "cubic",
+			     sizeof("cubic")));
+	refresh_connection();
+
+	name_len = sizeof(name);
+	TEST_RES(getsockopt(sk_accepted, IPPROTO_TCP, TCP_CONGESTION, name,
+			    &name_len),
+		 strcmp(name, "cubic") == 0);
+
+	TEST_SUCC(setsockopt(sk_accepted, IPPROTO_TCP, TCP_CONGESTION, "reno",
+			     sizeof("reno")));
+	name_len = sizeof(name);
+	TEST_RES(getsockopt(sk_accepted, IPPROTO_TCP, TCP_CONGESTION, name,
+			    &name_len),
+		 strcmp(name, "reno") == 0);
+
+	TEST_ERRNO(setsockopt(sk_accepted, IPPROTO_TCP, TCP_CONGESTION,
+			      "unknown", sizeof("unknown")),
+		   ENOENT);
+}
+END_TEST()
+
+FN_TEST(linger_zero)
+{
+	struct linger linger = { .l_onoff = 1, .l_linger = 0 };
+	char buf[1];
+
+	refresh_connection();
+
+	TEST_SUCC(setsockopt(sk_connected, SOL_SOCKET, SO_LINGER, &linger,
+			     sizeof(linger)));
+	TEST_SUCC(close(sk_connected));
+
+	// Closing the socket with a zero linger timeout resets the connection.
+	TEST_ERRNO(recv(sk_accepted, buf, sizeof(buf), 0), ECONNRESET);
+
+	sk_connected = -1;
+	refresh_connection();
+}
+END_TEST()
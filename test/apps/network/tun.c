@@ -0,0 +1,229 @@
+// SPDX-License-Identifier: MPL-2.0
+
+#include <fcntl.h>
+#include <string.h>
+#include <sys/ioctl.h>
+#include <sys/socket.h>
+#include <linux/if_tun.h>
+#include <linux/netlink.h>
+#include <linux/rtnetlink.h>
+#include <netinet/in.h>
+#include <netinet/ip.h>
+#include <netinet/udp.h>
+#include <arpa/inet.h>
+#include <net/if.h>
+#include <unistd.h>
+
+#include "test.h"
+
+#define TUN_ADDR "10.9.0.1"
+#define PEER_ADDR "10.9.0.2"
+#define PEER_PORT 0x1270
+
+static int tun_fd;
+static struct ifreq tun_ifr;
+
+FN_SETUP(open)
+{
+	tun_fd = CHECK(open("/dev/net/tun", O_RDWR));
+}
+END_SETUP()
+
+FN_TEST(not_attached)
+{
+	char buf[64] = {};
+	struct ifreq ifr;
+
+	TEST_ERRNO(read(tun_fd, buf, sizeof(buf)), EBADFD);
+	TEST_ERRNO(write(tun_fd, buf, sizeof(buf)), EBADFD);
+	TEST_ERRNO(ioctl(tun_fd, TUNGETIFF, &ifr), EBADFD);
+}
+END_TEST()
+
+FN_TEST(invalid_flags)
+{
+	struct ifreq ifr = {};
+
+	ifr.ifr_flags = IFF_NO_PI;
+	TEST_ERRNO(ioctl(tun_fd, TUNSETIFF, &ifr), EINVAL);
+
+	ifr.ifr_flags = IFF_TUN | IFF_TAP;
+	TEST_ERRNO(ioctl(tun_fd, TUNSETIFF, &ifr), EINVAL);
+}
+END_TEST()
+
+FN_TEST(set_iff)
+{
+	struct ifreq ifr = {};
+
+	tun_ifr.ifr_flags = IFF_TUN | IFF_NO_PI;
+	TEST_RES(ioctl(tun_fd, TUNSETIFF, &tun_ifr),
+		 strncmp(tun_ifr.ifr_name, "tun", 3) == 0);
+
+	TEST_RES(ioctl(tun_fd, TUNGETIFF, &ifr),
+		 strcmp(ifr.ifr_name, tun_ifr.ifr_name) == 0 &&
+			 ifr.ifr_flags == (IFF_TUN | IFF_NO_PI));
+
+	TEST_ERRNO(ioctl(tun_fd, TUNSETIFF, &tun_ifr), EEXIST);
+}
+END_TEST()
+
+FN_TEST(name_in_use)
+{
+	struct ifreq ifr = {};
+	int fd;
+
+	fd = TEST_SUCC(open("/dev/net/tun", O_RDWR));
+
+	strcpy(ifr.ifr_name, tun_ifr.ifr_name);
+	ifr.ifr_flags = IFF_TAP;
+	TEST_ERRNO(ioctl(fd, TUNSETIFF, &ifr), EBUSY);
+
+	strcpy(ifr.ifr_name, "lo");
+	TEST_ERRNO(ioctl(fd, TUNSETIFF, &ifr), EBUSY);
+
+	strcpy(ifr.ifr_name, "tuntest%d");
+	TEST_RES(ioctl(fd, TUNSETIFF, &ifr),
+		 strcmp(ifr.ifr_name, "tuntest0") == 0);
+
+	TEST_SUCC(close(fd));
+
+	// The name can be reused after the file is closed.
+	fd = TEST_SUCC(open("/dev/net/tun", O_RDWR));
+	strcpy(ifr.ifr_name, "tuntest0");
+	ifr.ifr_flags = IFF_TAP;
+	TEST_RES(ioctl(fd, TUNSETIFF, &ifr),
+		 strcmp(ifr.ifr_name, "tuntest0") == 0);
+	TEST_SUCC(close(fd));
+}
+END_TEST()
+
+FN_TEST(invalid_packets)
+{
+	char buf[2000] = {};
+
+	TEST_ERRNO(write(tun_fd, buf, 0), EINVAL);
+	TEST_ERRNO(write(tun_fd, buf, sizeof(buf)), EINVAL);
+}
+END_TEST()
+
+struct request {
+	struct nlmsghdr hdr;
+	union {
+		struct ifinfomsg ifinfo;
+		struct ifaddrmsg ifaddr;
+	};
+	char attrs[64];
+};
+
+static int tun_index;
+
+// Finds the index of the TUN iface and assigns an address to it.
+FN_SETUP(add_addr)
+{
+	char buf[8192];
+	struct request req = {};
+	struct rtattr *attr = IFA_RTA(&req.ifaddr);
+	struct sockaddr_nl addr = { .nl_family = AF_NETLINK };
+	struct nlmsghdr *hdr;
+	int sk, len;
+
+	sk = CHECK(socket(AF_NETLINK, SOCK_RAW, NETLINK_ROUTE));
+	CHECK(bind(sk, (struct sockaddr *)&addr, sizeof(addr)));
+
+	req.hdr.nlmsg_len = NLMSG_LENGTH(sizeof(req.ifinfo));
+	req.hdr.nlmsg_type = RTM_GETLINK;
+	req.hdr.nlmsg_flags = NLM_F_REQUEST | NLM_F_DUMP;
+	req.ifinfo.ifi_family = AF_UNSPEC;
+	CHECK(send(sk, &req, req.hdr.nlmsg_len, 0));
+
+	while (tun_index == 0) {
+		len = CHECK(recv(sk, buf, sizeof(buf), 0));
+		for (hdr = (struct nlmsghdr *)buf; NLMSG_OK(hdr, len);
+		     hdr = NLMSG_NEXT(hdr, len)) {
+			struct ifinfomsg *ifinfo = NLMSG_DATA(hdr);
+			struct rtattr *link_attr = IFLA_RTA(ifinfo);
+			int attr_len = IFLA_PAYLOAD(hdr);
+
+			CHECK_WITH(hdr->nlmsg_type, _ret != NLMSG_DONE);
+			for (; RTA_OK(link_attr, attr_len);
+			     link_attr = RTA_NEXT(link_attr, attr_len)) {
+				if (link_attr->rta_type == IFLA_IFNAME &&
+				    strcmp(RTA_DATA(link_attr),
+					   tun_ifr.ifr_name) == 0)
+					tun_index = ifinfo->ifi_index;
+			}
+		}
+	}
+	// Drain the rest of the dump.
+	while (recv(sk, buf, sizeof(buf), MSG_DONTWAIT) > 0)
+		;
+
+	memset(&req, 0, sizeof(req));
+	req.ifaddr.ifa_family = AF_INET;
+	req.ifaddr.ifa_prefixlen = 24;
+	req.ifaddr.ifa_index = tun_index;
+	attr->rta_type = IFA_LOCAL;
+	attr->rta_len = RTA_LENGTH(sizeof(in_addr_t));
+	*(in_addr_t *)RTA_DATA(attr) = inet_addr(TUN_ADDR);
+	req.hdr.nlmsg_len =
+		NLMSG_LENGTH(sizeof(req.ifaddr) + attr->rta_len);
+	req.hdr.nlmsg_type = RTM_NEWADDR;
+	req.hdr.nlmsg_flags = NLM_F_REQUEST | NLM_F_CREATE | NLM_F_EXCL;
+	CHECK(send(sk, &req, req.hdr.nlmsg_len, 0));
+
+	CHECK(close(sk));
+}
+END_SETUP()
+
+FN_TEST(udp_round_trip)
+{
+	struct sockaddr_in peer_addr = { .sin_family = AF_INET,
+					 .sin_port = htons(PEER_PORT) };
+	char buf[1500];
+	struct iphdr *ip = (struct iphdr *)buf;
+	struct udphdr *udp;
+	uint32_t ip_addr;
+	uint16_t port;
+	int sk;
+
+	peer_addr.sin_addr.s_addr = inet_addr(PEER_ADDR);
+
+	sk = TEST_SUCC(socket(AF_INET, SOCK_DGRAM, 0));
+	TEST_RES(sendto(sk, "hello", 6, 0, (struct sockaddr *)&peer_addr,
+			sizeof(peer_addr)),
+		 _ret == 6);
+
+	// The packet sent by the socket is read from the TUN file.
+	TEST_RES(read(tun_fd, buf, sizeof(buf)),
+		 _ret == sizeof(struct iphdr) + sizeof(struct udphdr) + 6 &&
+			 ip->version == 4 && ip->protocol == IPPROTO_UDP &&
+			 ip->saddr == inet_addr(TUN_ADDR) &&
+			 ip->daddr == inet_addr(PEER_ADDR));
+	udp = (struct udphdr *)(buf + ip->ihl * 4);
+	TEST_RES(0, ntohs(udp->dest) == PEER_PORT &&
+			    memcmp(udp + 1, "hello", 6) == 0);
+
+	// Swapping the addresses and the ports keeps the checksums valid.
+	ip_addr = ip->saddr;
+	ip->saddr = ip->daddr;
+	ip->daddr = ip_addr;
+	port = udp->source;
+	udp->source = udp->dest;
+	udp->dest = port;
+
+	// The packet written to the TUN file is received by the socket.
+	TEST_RES(write(tun_fd, buf, ntohs(ip->tot_len)),
+		 _ret == ntohs(ip->tot_len));
+	TEST_RES(recv(sk, buf, sizeof(buf), 0),
+		 _ret == 6 && memcmp(buf, "hello", 6) == 0);
+
+	TEST_SUCC(close(sk));
+}
+END_TEST()
+
+FN_SETUP(cleanup)
+{
+	CHECK(close(tun_fd));
+}
+END_SETUP()
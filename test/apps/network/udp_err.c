@@ -402,3 +402,22 @@ FN_TEST(self_connect)
 	TEST_SUCC(close(sk));
 }
 END_TEST()
+
+FN_TEST(connect_loopback_subnet)
+{
+	struct sockaddr_in addr = sk_addr;
+	socklen_t addrlen = sizeof(addr);
+	int sk;
+
+	sk = TEST_SUCC(socket(PF_INET, SOCK_DGRAM, 0));
+
+	// The local address is selected by the route to the loopback subnet.
+	CHECK(inet_aton("127.0.0.2", &addr.sin_addr));
+	TEST_SUCC(connect(sk, (struct sockaddr *)&addr, sizeof(addr)));
+	TEST_RES(getsockname(sk, (struct sockaddr *)&addr, &addrlen),
+		 addrlen == sizeof(addr) &&
+			 addr.sin_addr.s_addr == htonl(INADDR_LOOPBACK));
+
+	TEST_SUCC(close(sk));
+}
+END_TEST()
@@ -0,0 +1,280 @@
+// SPDX-License-Identifier: MPL-2.0
+
+#define _GNU_SOURCE
+
+#include <sys/socket.h>
+#include <sys/un.h>
+#include <fcntl.h>
+#include <unistd.h>
+#include <stddef.h>
+
+#include "test.h"
+
+static int sk_pair[2];
+static int pipe_fds[2];
+
+FN_SETUP(socketpair)
+{
+	CHECK(socketpair(PF_UNIX, SOCK_STREAM, 0, sk_pair));
+	CHECK(pipe(pipe_fds));
+}
+END_SETUP()
+
+static int send_fds(int sk, const int *fds, int nfds, const char *data,
+		    size_t len)
+{
+	char cbuf[CMSG_SPACE(sizeof(int) * 4)];
+	struct iovec iov = { .iov_base = (void *)data, .iov_len = len };
+	struct msghdr msg = { .msg_iov = &iov, .msg_iovlen = 1 };
+	struct cmsghdr *cmsg;
+
+	memset(cbuf, 0, sizeof(cbuf));
+	msg.msg_control = cbuf;
+	msg.msg_controllen = CMSG_SPACE(sizeof(int) * nfds);
+
+	cmsg = CMSG_FIRSTHDR(&msg);
+	cmsg->cmsg_level = SOL_SOCKET;
+	cmsg->cmsg_type = SCM_RIGHTS;
+	cmsg->cmsg_len = CMSG_LEN(sizeof(int) * nfds);
+	memcpy(CMSG_DATA(cmsg), fds, sizeof(int) * nfds);
+
+	return sendmsg(sk, &msg, 0);
+}
+
+FN_TEST(scm_rights)
+{
+	char buf[8];
+	char cbuf[CMSG_SPACE(sizeof(int))];
+	struct iovec iov = { .iov_base = buf, .iov_len = sizeof(buf) };
+	struct msghdr msg = { .msg_iov = &iov, .msg_iovlen = 1 };
+	struct cmsghdr *cmsg;
+	int fd;
+
+	TEST_RES(send_fds(sk_pair[0], &pipe_fds[0], 1, "cd", 2), _ret == 2);
+	TEST_RES(send(sk_pair[0], "ef", 2, 0), _ret == 2);
+
+	msg.msg_control = cbuf;
+	msg.msg_controllen = sizeof(cbuf);
+	TEST_RES(recvmsg(sk_pair[1], &msg, MSG_CMSG_CLOEXEC),
+		 _ret == 2 && memcmp(buf, "cd", 2) == 0 &&
+			 msg.msg_controllen == CMSG_SPACE(sizeof(int)));
+
+	cmsg = CMSG_FIRSTHDR(&msg);
+	TEST_RES(0, cmsg != NULL && cmsg->cmsg_level == SOL_SOCKET &&
+			    cmsg->cmsg_type == SCM_RIGHTS &&
+			    cmsg->cmsg_len == CMSG_LEN(sizeof(int)));
+	memcpy(&fd, CMSG_DATA(cmsg), sizeof(int));
+
+	// The received file descriptor refers to the read end of the pipe
+	TEST_RES(fcntl(fd, F_GETFD), _ret == FD_CLOEXEC);
+	TEST_RES(write(pipe_fds[1], "x", 1), _ret == 1);
+	TEST_RES(read(fd, buf, sizeof(buf)), _ret == 1 && buf[0] == 'x');
+	TEST_SUCC(close(fd));
+
+	// The subsequent data carries no control messages
+	msg.msg_control = cbuf;
+	msg.msg_controllen = sizeof(cbuf);
+	TEST_RES(recvmsg(sk_pair[1], &msg, 0),
+		 _ret == 2 && memcmp(buf, "ef", 2) == 0 &&
+			 msg.msg_controllen == 0);
+}
+END_TEST()
+
+FN_TEST(scm_rights_ctrunc)
+{
+	char buf[8];
+	char cbuf[CMSG_SPACE(sizeof(int) * 2)];
+	struct iovec iov = { .iov_base = buf, .iov_len = sizeof(buf) };
+	struct msghdr msg = { .msg_iov = &iov, .msg_iovlen = 1 };
+	struct cmsghdr *cmsg;
+	int fd;
+
+	TEST_RES(send_fds(sk_pair[0], pipe_fds, 2, "a", 1), _ret == 1);
+
+	// There is only room for one file descriptor
+	msg.msg_control = cbuf;
+	msg.msg_controllen = CMSG_LEN(sizeof(int));
+	TEST_RES(recvmsg(sk_pair[1], &msg, 0),
+		 _ret == 1 && buf[0] == 'a' && msg.msg_flags == MSG_CTRUNC &&
+			 msg.msg_controllen == CMSG_LEN(sizeof(int)));
+
+	cmsg = CMSG_FIRSTHDR(&msg);
+	TEST_RES(0, cmsg != NULL && cmsg->cmsg_type == SCM_RIGHTS &&
+			    cmsg->cmsg_len == CMSG_LEN(sizeof(int)));
+	memcpy(&fd, CMSG_DATA(cmsg), sizeof(int));
+	TEST_RES(fcntl(fd, F_GETFD), _ret == 0);
+	TEST_SUCC(close(fd));
+
+	// No room for control messages at all
+	TEST_RES(send_fds(sk_pair[0], pipe_fds, 1, "b", 1), _ret == 1);
+
+	msg.msg_control = NULL;
+	msg.msg_controllen = 0;
+	TEST_RES(recvmsg(sk_pair[1], &msg, 0),
+		 _ret == 1 && buf[0] == 'b' && msg.msg_flags == MSG_CTRUNC);
+}
+END_TEST()
+
+FN_TEST(scm_rights_errors)
+{
+	char cbuf[CMSG_SPACE(sizeof(int))];
+	struct iovec iov = { .iov_base = "z", .iov_len = 1 };
+	struct msghdr msg = { .msg_iov = &iov, .msg_iovlen = 1 };
+	struct cmsghdr *cmsg;
+	int bad_fd = 1000;
+
+	TEST_ERRNO(send_fds(sk_pair[0], &bad_fd, 1, "z", 1), EBADF);
+
+	memset(cbuf, 0, sizeof(cbuf));
+	msg.msg_control = cbuf;
+	msg.msg_controllen = sizeof(cbuf);
+	cmsg = CMSG_FIRSTHDR(&msg);
+	cmsg->cmsg_level = SOL_SOCKET;
+	cmsg->cmsg_type = SCM_RIGHTS;
+	cmsg->cmsg_len = sizeof(cbuf) + 1;
+	TEST_ERRNO(sendmsg(sk_pair[0], &msg, 0), EINVAL);
+
+	cmsg->cmsg_len = sizeof(struct cmsghdr) - 1;
+	TEST_ERRNO(sendmsg(sk_pair[0], &msg, 0), EINVAL);
+}
+END_TEST()
+
+FN_TEST(scm_credentials)
+{
+	char buf[8];
+	char cbuf[CMSG_SPACE(sizeof(struct ucred))];
+	struct iovec iov = { .iov_base = "c", .iov_len = 1 };
+	struct msghdr msg = { .msg_iov = &iov, .msg_iovlen = 1 };
+	struct cmsghdr *cmsg;
+	struct ucred cred = { .pid = getpid(),
+			      .uid = geteuid(),
+			      .gid = getegid() };
+
+	memset(cbuf, 0, sizeof(cbuf));
+	msg.msg_control = cbuf;
+	msg.msg_controllen = sizeof(cbuf);
+	cmsg = CMSG_FIRSTHDR(&msg);
+	cmsg->cmsg_level = SOL_SOCKET;
+	cmsg->cmsg_type = SCM_CREDENTIALS;
+	cmsg->cmsg_len = CMSG_LEN(sizeof(struct ucred));
+	memcpy(CMSG_DATA(cmsg), &cred, sizeof(cred));
+	TEST_RES(sendmsg(sk_pair[0], &msg, 0), _ret == 1);
+
+	// Without `SO_PASSCRED`, the credentials are not received
+	iov.iov_base = buf;
+	iov.iov_len = sizeof(buf);
+	msg.msg_controllen = sizeof(cbuf);
+	TEST_RES(recvmsg(sk_pair[1], &msg, 0),
+		 _ret == 1 && buf[0] == 'c' && msg.msg_controllen == 0);
+}
+END_TEST()
+
+FN_TEST(peer_cred)
+{
+	struct ucred cred;
+	socklen_t len;
+	int sk;
+
+	len = sizeof(cred);
+	TEST_RES(getsockopt(sk_pair[0], SOL_SOCKET, SO_PEERCRED, &cred, &len),
+		 len == sizeof(cred) && cred.pid == getpid() &&
+			 cred.uid == geteuid() && cred.gid == getegid());
+
+	sk = TEST_SUCC(socket(PF_UNIX, SOCK_STREAM, 0));
+	len = sizeof(cred);
+	TEST_RES(getsockopt(sk, SOL_SOCKET, SO_PEERCRED, &cred, &len),
+		 len == sizeof(cred) && cred.pid == 0 && cred.uid == -1 &&
+			 cred.gid == -1);
+	TEST_SUCC(close(sk));
+}
+END_TEST()
+
+#define ABSTRACT_ADDR "\0/tmp/ancillary"
+
+FN_TEST(peer_cred_connected)
+{
+	struct sockaddr_un addr = { .sun_family = AF_UNIX };
+	socklen_t addrlen =
+		offsetof(struct sockaddr_un, sun_path) + sizeof(ABSTRACT_ADDR);
+	struct ucred cred;
+	socklen_t len;
+	int sk_listen, sk_connect, sk_accept;
+
+	memcpy(addr.sun_path, ABSTRACT_ADDR, sizeof(ABSTRACT_ADDR));
+
+	sk_listen = TEST_SUCC(socket(PF_UNIX, SOCK_STREAM, 0));
+	TEST_SUCC(bind(sk_listen, (struct sockaddr *)&addr, addrlen));
+	TEST_SUCC(listen(sk_listen, 1));
+
+	sk_connect = TEST_SUCC(socket(PF_UNIX, SOCK_STREAM, 0));
+	TEST_SUCC(connect(sk_connect, (struct sockaddr *)&addr, addrlen));
+	sk_accept = TEST_SUCC(accept(sk_listen, NULL, NULL));
+
+	len = sizeof(cred);
+	TEST_RES(getsockopt(sk_connect, SOL_SOCKET, SO_PEERCRED, &cred, &len),
+		 len == sizeof(cred) && cred.pid == getpid() &&
+			 cred.uid == geteuid());
+	len = sizeof(cred);
+	TEST_RES(getsockopt(sk_accept, SOL_SOCKET, SO_PEERCRED, &cred, &len),
+		 len == sizeof(cred) && cred.pid == getpid() &&
+			 cred.uid == geteuid());
+
+	TEST_SUCC(close(sk_accept));
+	TEST_SUCC(close(sk_connect));
+	TEST_SUCC(close(sk_listen));
+}
+END_TEST()
+
+FN_TEST(seqpacket_boundaries)
+{
+	int sks[2];
+	char buf[8];
+	static char big_buf[300000];
+
+	TEST_SUCC(socketpair(PF_UNIX, SOCK_SEQPACKET | SOCK_NONBLOCK, 0, sks));
+
+	TEST_RES(send(sks[0], "abc", 3, 0), _ret == 3);
+	TEST_RES(send(sks[0], "defgh", 5, 0), _ret == 5);
+	TEST_RES(send(sks[0], "ijklmn", 6, 0), _ret == 6);
+
+	TEST_RES(recv(sks[1], buf, sizeof(buf), 0),
+		 _ret == 3 && memcmp(buf, "abc", 3) == 0);
+	TEST_RES(recv(sks[1], buf, sizeof(buf), 0),
+		 _ret == 5 && memcmp(buf, "defgh", 5) == 0);
+
+	// The rest of a truncated message is discarded
+	TEST_RES(recv(sks[1], buf, 2, 0),
+		 _ret == 2 && memcmp(buf, "ij", 2) == 0);
+	TEST_ERRNO(recv(sks[1], buf, sizeof(buf), 0), EAGAIN);
+
+	TEST_ERRNO(send(sks[0], big_buf, sizeof(big_buf), 0), EMSGSIZE);
+
+	TEST_SUCC(close(sks[0]));
+	TEST_SUCC(close(sks[1]));
+}
+END_TEST()
+
+FN_TEST(seqpacket_type_mismatch)
+{
+	struct sockaddr_un addr = { .sun_family = AF_UNIX,
+				    .sun_path = "/tmp/seqpacket" };
+	socklen_t addrlen = sizeof(addr);
+	int sk_listen, sk_connect;
+
+	sk_listen = TEST_SUCC(socket(PF_UNIX, SOCK_SEQPACKET, 0));
+	TEST_SUCC(bind(sk_listen, (struct sockaddr *)&addr, addrlen));
+	TEST_SUCC(listen(sk_listen, 1));
+
+	sk_connect = TEST_SUCC(socket(PF_UNIX, SOCK_STREAM, 0));
+	TEST_ERRNO(connect(sk_connect, (struct sockaddr *)&addr, addrlen),
+		   EPROTOTYPE);
+	TEST_SUCC(close(sk_connect));
+
+	sk_connect = TEST_SUCC(socket(PF_UNIX, SOCK_SEQPACKET, 0));
+	TEST_SUCC(connect(sk_connect, (struct sockaddr *)&addr, addrlen));
+	TEST_SUCC(close(sk_connect));
+
+	TEST_SUCC(close(sk_listen));
+	TEST_SUCC(unlink(addr.sun_path));
+}
+END_TEST()
@@ -0,0 +1,114 @@
+// SPDX-License-Identifier: MPL-2.0
+
+#define _GNU_SOURCE
+
+#include <string.h>
+#include <sys/socket.h>
+#include <netinet/in.h>
+#include <arpa/inet.h>
+#include <unistd.h>
+
+#include "test.h"
+
+#define TCP_PORT htons(0x1250)
+#define UDP_PORT htons(0x1251)
+
+static int sk_listen;
+static int sk_connect;
+static int sk_accept;
+static int sk_udp_recv;
+static int sk_udp_send;
+static struct sockaddr_in udp_addr;
+static char buf[4096];
+
+FN_SETUP(tcp)
+{
+	struct sockaddr_in addr = { .sin_family = AF_INET,
+				    .sin_port = TCP_PORT };
+
+	addr.sin_addr.s_addr = htonl(INADDR_LOOPBACK);
+
+	sk_listen = CHECK(socket(AF_INET, SOCK_STREAM, 0));
+	CHECK(bind(sk_listen, (struct sockaddr *)&addr, sizeof(addr)));
+	CHECK(listen(sk_listen, 1));
+
+	sk_connect = CHECK(socket(AF_INET, SOCK_STREAM, 0));
+	CHECK(connect(sk_connect, (struct sockaddr *)&addr, sizeof(addr)));
+
+	sk_accept = CHECK(accept(sk_listen, NULL, NULL));
+}
+END_SETUP()
+
+FN_SETUP(udp)
+{
+	udp_addr.sin_family = AF_INET;
+	udp_addr.sin_port = UDP_PORT;
+	udp_addr.sin_addr.s_addr = htonl(INADDR_LOOPBACK);
+
+	sk_udp_recv = CHECK(socket(AF_INET, SOCK_DGRAM, 0));
+	CHECK(bind(sk_udp_recv, (struct sockaddr *)&udp_addr,
+		   sizeof(udp_addr)));
+
+	sk_udp_send = CHECK(socket(AF_INET, SOCK_DGRAM, 0));
+}
+END_SETUP()
+
+FN_TEST(default_option)
+{
+	int zerocopy;
+	socklen_t optlen = sizeof(zerocopy);
+
+	TEST_RES(getsockopt(sk_connect, SOL_SOCKET, SO_ZEROCOPY, &zerocopy,
+			    &optlen),
+		 optlen == sizeof(zerocopy) && zerocopy == 0);
+
+	TEST_ERRNO(recv(sk_connect, buf, sizeof(buf), MSG_ERRQUEUE), EAGAIN);
+}
+END_TEST()
+
+FN_TEST(tcp_without_option)
+{
+	// `MSG_ZEROCOPY` is ignored if `SO_ZEROCOPY` is not enabled.
+	TEST_RES(send(sk_connect, buf, sizeof(buf), MSG_ZEROCOPY),
+		 _ret == sizeof(buf));
+	TEST_RES(recv(sk_accept, buf, sizeof(buf), MSG_WAITALL),
+		 _ret == sizeof(buf));
+
+	TEST_ERRNO(recv(sk_connect, buf, sizeof(buf), MSG_ERRQUEUE), EAGAIN);
+}
+END_TEST()
+
+FN_TEST(udp_without_option)
+{
+	TEST_RES(sendto(sk_udp_send, buf, 1024, MSG_ZEROCOPY,
+			(struct sockaddr *)&udp_addr, sizeof(udp_addr)),
+		 _ret == 1024);
+	TEST_RES(recv(sk_udp_recv, buf, sizeof(buf), 0), _ret == 1024);
+
+	TEST_ERRNO(recv(sk_udp_send, buf, sizeof(buf), MSG_ERRQUEUE), EAGAIN);
+}
+END_TEST()
+
+FN_TEST(unsupported_option)
+{
+	int zerocopy = 1;
+
+	// Zero-copy transmission is not supported, so `SO_ZEROCOPY` cannot be enabled.
+	TEST_ERRNO(setsockopt(sk_connect, SOL_SOCKET, SO_ZEROCOPY, &zerocopy,
+			      sizeof(zerocopy)),
+		   EOPNOTSUPP);
+	TEST_ERRNO(setsockopt(sk_udp_send, SOL_SOCKET, SO_ZEROCOPY, &zerocopy,
+			      sizeof(zerocopy)),
+		   EOPNOTSUPP);
+}
+END_TEST()
+
+FN_SETUP(cleanup)
+{
+	CHECK(close(sk_listen));
+	CHECK(close(sk_connect));
+	CHECK(close(sk_accept));
+	CHECK(close(sk_udp_recv));
+	CHECK(close(sk_udp_send));
+}
+END_SETUP()
@@ -0,0 +1,107 @@
+// SPDX-License-Identifier: MPL-2.0
+
+#define _GNU_SOURCE
+
+#include <fcntl.h>
+#include <string.h>
+#include <sys/uio.h>
+#include <unistd.h>
+
+#include "../network/test.h"
+
+static int rfd, wfd;
+
+FN_SETUP(pipe)
+{
+	int fildes[2];
+
+	CHECK(pipe2(fildes, O_NONBLOCK | O_DIRECT));
+	rfd = fildes[0];
+	wfd = fildes[1];
+}
+END_SETUP()
+
+FN_TEST(pipe2_flags)
+{
+	int fildes[2];
+
+	TEST_ERRNO(pipe2(fildes, O_APPEND), EINVAL);
+
+	TEST_RES(fcntl(rfd, F_GETFL), (_ret & (O_NONBLOCK | O_DIRECT)) ==
+					      (O_NONBLOCK | O_DIRECT));
+	TEST_RES(fcntl(wfd, F_GETFL), (_ret & (O_NONBLOCK | O_DIRECT)) ==
+					      (O_NONBLOCK | O_DIRECT));
+}
+END_TEST()
+
+FN_TEST(packet_mode)
+{
+	char buf[16] = { 0 };
+
+	TEST_RES(write(wfd, "hello", 5), _ret == 5);
+	TEST_RES(write(wfd, "world!", 6), _ret == 6);
+
+	// Each read returns one packet.
+	TEST_RES(read(rfd, buf, sizeof(buf)),
+		 _ret == 5 && memcmp(buf, "hello", 5) == 0);
+
+	// The remaining bytes of a packet are discarded.
+	TEST_RES(read(rfd, buf, 3), _ret == 3 && memcmp(buf, "wor", 3) == 0);
+	TEST_ERRNO(read(rfd, buf, sizeof(buf)), EAGAIN);
+}
+END_TEST()
+
+FN_TEST(pipe_size)
+{
+	char buf[8] = { 0 };
+
+	TEST_RES(fcntl(rfd, F_GETPIPE_SZ), _ret == 65536);
+
+	// The size is rounded up to a power of two, and is at least one page.
+	TEST_RES(fcntl(wfd, F_SETPIPE_SZ, 1), _ret == 4096);
+	TEST_RES(fcntl(rfd, F_GETPIPE_SZ), _ret == 4096);
+	TEST_RES(fcntl(rfd, F_SETPIPE_SZ, 5000), _ret == 8192);
+	TEST_RES(fcntl(wfd, F_GETPIPE_SZ), _ret == 8192);
+
+	TEST_ERRNO(fcntl(STDIN_FILENO, F_GETPIPE_SZ), EBADF);
+
+	// The data is preserved after resizing.
+	TEST_RES(write(wfd, "abc", 3), _ret == 3);
+	TEST_RES(fcntl(wfd, F_SETPIPE_SZ, 65536), _ret == 65536);
+	TEST_RES(read(rfd, buf, sizeof(buf)),
+		 _ret == 3 && memcmp(buf, "abc", 3) == 0);
+}
+END_TEST()
+
+FN_TEST(vmsplice)
+{
+	char buf1[4] = { 0 }, buf2[4] = { 0 };
+	struct iovec in_iov[2] = {
+		{ .iov_base = "abc", .iov_len = 3 },
+		{ .iov_base = "defg", .iov_len = 4 },
+	};
+	struct iovec out_iov[2] = {
+		{ .iov_base = buf1, .iov_len = sizeof(buf1) },
+		{ .iov_base = buf2, .iov_len = sizeof(buf2) },
+	};
+
+	TEST_ERRNO(vmsplice(wfd, in_iov, 2, 0x10), EINVAL);
+	TEST_ERRNO(vmsplice(STDIN_FILENO, in_iov, 2, 0), EBADF);
+
+	TEST_SUCC(fcntl(wfd, F_SETFL, O_NONBLOCK));
+	TEST_SUCC(fcntl(rfd, F_SETFL, O_NONBLOCK));
+
+	TEST_RES(vmsplice(wfd, in_iov, 2, 0), _ret == 7);
+	TEST_RES(vmsplice(rfd, out_iov, 2, 0),
+		 _ret == 7 && memcmp(buf1, "abcd", 4) == 0 &&
+			 memcmp(buf2, "efg", 3) == 0);
+	TEST_ERRNO(vmsplice(rfd, out_iov, 2, SPLICE_F_NONBLOCK), EAGAIN);
+}
+END_TEST()
+
+FN_SETUP(cleanup)
+{
+	CHECK(close(rfd));
+	CHECK(close(wfd));
+}
+END_SETUP()
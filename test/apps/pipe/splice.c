@@ -0,0 +1,110 @@
+// SPDX-License-Identifier: MPL-2.0
+
+#define _GNU_SOURCE
+
+#include <fcntl.h>
+#include <string.h>
+#include <sys/sendfile.h>
+#include <unistd.h>
+
+#include "../network/test.h"
+
+#define SRC_FILE "/tmp/test_splice_src.txt"
+#define DST_FILE "/tmp/test_splice_dst.txt"
+#define CONTENT "Hello, splice!"
+#define CONTENT_LEN (sizeof(CONTENT) - 1)
+
+static int src_fd, dst_fd;
+static int rfd, wfd;
+
+FN_SETUP(files)
+{
+	int fildes[2];
+
+	src_fd = CHECK(open(SRC_FILE, O_RDWR | O_CREAT | O_TRUNC, 0644));
+	dst_fd = CHECK(open(DST_FILE, O_RDWR | O_CREAT | O_TRUNC, 0644));
+	CHECK_WITH(write(src_fd, CONTENT, CONTENT_LEN), _ret == CONTENT_LEN);
+
+	CHECK(pipe(fildes));
+	rfd = fildes[0];
+	wfd = fildes[1];
+}
+END_SETUP()
+
+FN_TEST(splice_invalid)
+{
+	loff_t off = 0;
+
+	TEST_ERRNO(splice(src_fd, NULL, dst_fd, NULL, 1, 0), EINVAL);
+	TEST_ERRNO(splice(rfd, &off, dst_fd, NULL, 1, 0), ESPIPE);
+	TEST_ERRNO(splice(src_fd, NULL, wfd, &off, 1, 0), ESPIPE);
+	TEST_ERRNO(splice(src_fd, NULL, wfd, NULL, 1, 0x10), EINVAL);
+	TEST_ERRNO(splice(rfd, NULL, dst_fd, NULL, 1, SPLICE_F_NONBLOCK),
+		   EAGAIN);
+}
+END_TEST()
+
+FN_TEST(splice_file_to_pipe_to_file)
+{
+	char buf[CONTENT_LEN] = { 0 };
+	loff_t off_in = 7, off_out = 0;
+
+	// The explicit offset is updated, but the file offset is untouched.
+	TEST_RES(splice(src_fd, &off_in, wfd, NULL, CONTENT_LEN, 0),
+		 _ret == CONTENT_LEN - 7 && off_in == CONTENT_LEN);
+	TEST_RES(lseek(src_fd, 0, SEEK_CUR), _ret == CONTENT_LEN);
+
+	TEST_RES(splice(rfd, NULL, dst_fd, &off_out, CONTENT_LEN, 0),
+		 _ret == CONTENT_LEN - 7 && off_out == CONTENT_LEN - 7);
+	TEST_RES(pread(dst_fd, buf, sizeof(buf), 0),
+		 _ret == CONTENT_LEN - 7 &&
+			 memcmp(buf, CONTENT + 7, CONTENT_LEN - 7) == 0);
+}
+END_TEST()
+
+FN_TEST(copy_file_range)
+{
+	char buf[CONTENT_LEN] = { 0 };
+	loff_t off_in = 0, off_out = 0;
+
+	TEST_ERRNO(copy_file_range(src_fd, NULL, dst_fd, NULL, 1, 1), EINVAL);
+	TEST_ERRNO(copy_file_range(src_fd, NULL, wfd, NULL, 1, 0), EINVAL);
+	TEST_ERRNO(copy_file_range(src_fd, &off_in, src_fd, &off_out, 2, 0),
+		   EINVAL);
+
+	TEST_SUCC(ftruncate(dst_fd, 0));
+	TEST_RES(copy_file_range(src_fd, &off_in, dst_fd, &off_out,
+				 CONTENT_LEN + 10, 0),
+		 _ret == CONTENT_LEN && off_in == CONTENT_LEN &&
+			 off_out == CONTENT_LEN);
+	TEST_RES(pread(dst_fd, buf, sizeof(buf), 0),
+		 _ret == CONTENT_LEN && memcmp(buf, CONTENT, CONTENT_LEN) == 0);
+
+	// The end of the source file has been reached.
+	TEST_RES(copy_file_range(src_fd, &off_in, dst_fd, &off_out, 1, 0),
+		 _ret == 0);
+}
+END_TEST()
+
+FN_TEST(sendfile_to_pipe)
+{
+	char buf[CONTENT_LEN] = { 0 };
+	off_t off = 0;
+
+	TEST_RES(sendfile(wfd, src_fd, &off, CONTENT_LEN),
+		 _ret == CONTENT_LEN && off == CONTENT_LEN);
+	TEST_RES(read(rfd, buf, sizeof(buf)),
+		 _ret == CONTENT_LEN && memcmp(buf, CONTENT, CONTENT_LEN) == 0);
+}
+END_TEST()
+
+FN_SETUP(cleanup)
+{
+	CHECK(close(src_fd));
+	CHECK(close(dst_fd));
+	CHECK(close(rfd));
+	CHECK(close(wfd));
+	CHECK(unlink(SRC_FILE));
+	CHECK(unlink(DST_FILE));
+}
+END_SETUP()
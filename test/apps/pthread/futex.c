@@ -0,0 +1,197 @@
+// SPDX-License-Identifier: MPL-2.0
+
+#include "../network/test.h"
+
+#include <linux/futex.h>
+#include <pthread.h>
+#include <stdint.h>
+#include <sys/syscall.h>
+#include <unistd.h>
+
+static long futex(uint32_t *uaddr, int op, uint32_t val, long val2,
+		  uint32_t *uaddr2, uint32_t val3)
+{
+	return syscall(SYS_futex, uaddr, op, val, val2, uaddr2, val3);
+}
+
+static pid_t gettid_(void)
+{
+	return syscall(SYS_gettid);
+}
+
+static uint32_t futex_a, futex_b;
+
+static void *wait_on_futex_a(void *arg)
+{
+	(void)arg;
+
+	while (__atomic_load_n(&futex_a, __ATOMIC_SEQ_CST) == 0)
+		futex(&futex_a, FUTEX_WAIT_PRIVATE, 0, 0, NULL, 0);
+
+	return NULL;
+}
+
+FN_TEST(cmp_requeue)
+{
+	pthread_t threads[3];
+	int i;
+
+	futex_a = 0;
+	futex_b = 0;
+
+	for (i = 0; i < 3; ++i)
+		TEST_SUCC(pthread_create(&threads[i], NULL, wait_on_futex_a,
+					 NULL));
+	// Wait for the threads to wait on the futex.
+	usleep(100 * 1000);
+
+	// The futex value does not match.
+	TEST_ERRNO(futex(&futex_a, FUTEX_CMP_REQUEUE_PRIVATE, 1, 1, &futex_b,
+			 1),
+		   EAGAIN);
+
+	// One thread is woken up, and the other two threads are requeued.
+	// The woken thread waits again since the futex value is still zero.
+	TEST_RES(futex(&futex_a, FUTEX_CMP_REQUEUE_PRIVATE, 1, 2, &futex_b,
+		       0),
+		 _ret == 3);
+	usleep(100 * 1000);
+
+	// The requeued threads are woken up via the new futex.
+	__atomic_store_n(&futex_a, 1, __ATOMIC_SEQ_CST);
+	TEST_RES(futex(&futex_b, FUTEX_WAKE_PRIVATE, 2, 0, NULL, 0),
+		 _ret == 2);
+	TEST_RES(futex(&futex_a, FUTEX_WAKE_PRIVATE, 1, 0, NULL, 0),
+		 _ret == 1);
+
+	for (i = 0; i < 3; ++i)
+		TEST_SUCC(pthread_join(threads[i], NULL));
+}
+END_TEST()
+
+FN_TEST(lock_pi)
+{
+	futex_a = 0;
+
+	TEST_SUCC(futex(&futex_a, FUTEX_LOCK_PI_PRIVATE, 0, 0, NULL, 0));
+	TEST_RES(0, futex_a == (uint32_t)gettid_());
+
+	TEST_ERRNO(futex(&futex_a, FUTEX_LOCK_PI_PRIVATE, 0, 0, NULL, 0),
+		   EDEADLK);
+	TEST_ERRNO(futex(&futex_a, FUTEX_TRYLOCK_PI_PRIVATE, 0, 0, NULL, 0),
+		   EDEADLK);
+
+	TEST_SUCC(futex(&futex_a, FUTEX_UNLOCK_PI_PRIVATE, 0, 0, NULL, 0));
+	TEST_RES(0, futex_a == 0);
+
+	TEST_ERRNO(futex(&futex_a, FUTEX_UNLOCK_PI_PRIVATE, 0, 0, NULL, 0),
+		   EPERM);
+
+	TEST_SUCC(futex(&futex_a, FUTEX_TRYLOCK_PI_PRIVATE, 0, 0, NULL, 0));
+	TEST_RES(0, futex_a == (uint32_t)gettid_());
+	TEST_SUCC(futex(&futex_a, FUTEX_UNLOCK_PI_PRIVATE, 0, 0, NULL, 0));
+}
+END_TEST()
+
+#define NTHREADS 4
+#define NLOOPS 1000
+
+static pthread_mutex_t pi_mutex;
+static long pi_counter;
+
+static void *increase_pi_counter(void *arg)
+{
+	int i;
+
+	(void)arg;
+
+	for (i = 0; i < NLOOPS; ++i) {
+		if (pthread_mutex_lock(&pi_mutex) != 0)
+			return (void *)1;
+		++pi_counter;
+		if (pthread_mutex_unlock(&pi_mutex) != 0)
+			return (void *)1;
+	}
+
+	return NULL;
+}
+
+FN_TEST(pi_mutex)
+{
+	pthread_mutexattr_t attr;
+	pthread_t threads[NTHREADS];
+	void *ret;
+	int i;
+
+	TEST_SUCC(pthread_mutexattr_init(&attr));
+	TEST_SUCC(pthread_mutexattr_setprotocol(&attr, PTHREAD_PRIO_INHERIT));
+	TEST_SUCC(pthread_mutex_init(&pi_mutex, &attr));
+
+	for (i = 0; i < NTHREADS; ++i)
+		TEST_SUCC(pthread_create(&threads[i], NULL, increase_pi_counter,
+					 NULL));
+	for (i = 0; i < NTHREADS; ++i)
+		TEST_RES(pthread_join(threads[i], &ret), ret == NULL);
+
+	TEST_RES(0, pi_counter == NTHREADS * NLOOPS);
+
+	TEST_SUCC(pthread_mutex_destroy(&pi_mutex));
+	TEST_SUCC(pthread_mutexattr_destroy(&attr));
+}
+END_TEST()
+
+static pthread_mutex_t robust_pi_mutex;
+
+static void *lock_and_exit(void *arg)
+{
+	(void)arg;
+
+	// Exit without unlocking the mutex.
+	if (pthread_mutex_lock(&robust_pi_mutex) != 0)
+		return (void *)1;
+
+	return NULL;
+}
+
+FN_TEST(robust_pi_mutex)
+{
+	pthread_mutexattr_t attr;
+	pthread_t thread;
+	void *ret;
+
+	TEST_SUCC(pthread_mutexattr_init(&attr));
+	TEST_SUCC(pthread_mutexattr_setprotocol(&attr, PTHREAD_PRIO_INHERIT));
+	TEST_SUCC(pthread_mutexattr_setrobust(&attr, PTHREAD_MUTEX_ROBUST));
+	TEST_SUCC(pthread_mutex_init(&robust_pi_mutex, &attr));
+
+	TEST_SUCC(pthread_create(&thread, NULL, lock_and_exit, NULL));
+	TEST_RES(pthread_join(thread, &ret), ret == NULL);
+
+	TEST_RES(pthread_mutex_lock(&robust_pi_mutex), _ret == EOWNERDEAD);
+	TEST_SUCC(pthread_mutex_consistent(&robust_pi_mutex));
+	TEST_SUCC(pthread_mutex_unlock(&robust_pi_mutex));
+
+	TEST_SUCC(pthread_mutex_destroy(&robust_pi_mutex));
+	TEST_SUCC(pthread_mutexattr_destroy(&attr));
+}
+END_TEST()
+
+FN_TEST(get_robust_list)
+{
+	struct robust_list_head head = { .list = { .next = &head.list } };
+	struct robust_list_head *old_head;
+	struct robust_list_head *got_head;
+	size_t len;
+
+	TEST_SUCC(syscall(SYS_get_robust_list, 0, &old_head, &len));
+	TEST_RES(0, len == sizeof(struct robust_list_head));
+
+	TEST_ERRNO(syscall(SYS_set_robust_list, &head, len + 1), EINVAL);
+
+	TEST_SUCC(syscall(SYS_set_robust_list, &head, len));
+	TEST_RES(syscall(SYS_get_robust_list, gettid_(), &got_head, &len),
+		 got_head == &head);
+
+	TEST_SUCC(syscall(SYS_set_robust_list, old_head, len));
+}
+END_TEST()
@@ -0,0 +1,223 @@
+// SPDX-License-Identifier: MPL-2.0
+
+#define _GNU_SOURCE
+
+#include "../network/test.h"
+
+#include <fcntl.h>
+#include <pthread.h>
+#include <sched.h>
+#include <signal.h>
+#include <sys/syscall.h>
+#include <sys/wait.h>
+#include <unistd.h>
+
+static pid_t gettid_(void)
+{
+	return syscall(SYS_gettid);
+}
+
+static int tgkill_(pid_t tgid, pid_t tid, int sig)
+{
+	return syscall(SYS_tgkill, tgid, tid, sig);
+}
+
+static int tkill_(pid_t tid, int sig)
+{
+	return syscall(SYS_tkill, tid, sig);
+}
+
+static volatile pid_t thread_tid;
+static volatile int signaled_tid;
+static volatile int thread_done;
+
+static void handle_sigusr1(int sig)
+{
+	(void)sig;
+	signaled_tid = gettid_();
+}
+
+static void *wait_for_signal(void *arg)
+{
+	(void)arg;
+
+	thread_tid = gettid_();
+	while (!thread_done)
+		usleep(10 * 1000);
+
+	return NULL;
+}
+
+FN_TEST(tgkill)
+{
+	pthread_t thread;
+
+	signal(SIGUSR1, handle_sigusr1);
+	thread_tid = 0;
+	signaled_tid = 0;
+	thread_done = 0;
+
+	TEST_SUCC(pthread_create(&thread, NULL, wait_for_signal, NULL));
+	while (thread_tid == 0)
+		usleep(10 * 1000);
+
+	// The signal is delivered to the target thread.
+	TEST_SUCC(tgkill_(getpid(), thread_tid, SIGUSR1));
+	while (signaled_tid == 0)
+		usleep(10 * 1000);
+	TEST_RES(0, signaled_tid == thread_tid);
+
+	// The thread does not belong to the thread group.
+	TEST_ERRNO(tgkill_(getppid(), thread_tid, 0), ESRCH);
+
+	TEST_ERRNO(tgkill_(0, thread_tid, 0), EINVAL);
+	TEST_ERRNO(tgkill_(getpid(), -1, 0), EINVAL);
+	TEST_ERRNO(tgkill_(getpid(), thread_tid, 1000), EINVAL);
+
+	TEST_SUCC(tgkill_(getpid(), thread_tid, 0));
+
+	thread_done = 1;
+	TEST_SUCC(pthread_join(thread, NULL));
+	signal(SIGUSR1, SIG_DFL);
+}
+END_TEST()
+
+FN_TEST(tkill)
+{
+	pthread_t thread;
+
+	signal(SIGUSR1, handle_sigusr1);
+	thread_tid = 0;
+	signaled_tid = 0;
+	thread_done = 0;
+
+	TEST_SUCC(pthread_create(&thread, NULL, wait_for_signal, NULL));
+	while (thread_tid == 0)
+		usleep(10 * 1000);
+
+	TEST_SUCC(tkill_(thread_tid, SIGUSR1));
+	while (signaled_tid == 0)
+		usleep(10 * 1000);
+	TEST_RES(0, signaled_tid == thread_tid);
+
+	TEST_ERRNO(tkill_(0, 0), EINVAL);
+	TEST_ERRNO(tkill_(-1, 0), EINVAL);
+
+	thread_done = 1;
+	TEST_SUCC(pthread_join(thread, NULL));
+	signal(SIGUSR1, SIG_DFL);
+
+	// The thread has exited.
+	TEST_ERRNO(tkill_(thread_tid, 0), ESRCH);
+}
+END_TEST()
+
+static void *exit_group_in_thread(void *arg)
+{
+	(void)arg;
+
+	syscall(SYS_exit_group, 3);
+	return NULL;
+}
+
+FN_TEST(exit_group)
+{
+	pthread_t thread;
+	int status;
+	pid_t pid;
+
+	pid = TEST_SUCC(fork());
+	if (pid == 0) {
+		pthread_create(&thread, NULL, exit_group_in_thread, NULL);
+		// The main thread is killed by the other thread.
+		for (;;)
+			pause();
+	}
+
+	TEST_RES(waitpid(pid, &status, 0),
+		 _ret == pid && WIFEXITED(status) && WEXITSTATUS(status) == 3);
+}
+END_TEST()
+
+static char child_stack[16 * 1024] __attribute__((aligned(16)));
+
+static int do_nothing(void *arg)
+{
+	(void)arg;
+	return 0;
+}
+
+FN_TEST(invalid_flags)
+{
+	char *stack = child_stack + sizeof(child_stack);
+
+	TEST_ERRNO(clone(do_nothing, stack, CLONE_SIGHAND, NULL), EINVAL);
+	TEST_ERRNO(clone(do_nothing, stack, CLONE_VM | CLONE_THREAD, NULL),
+		   EINVAL);
+	TEST_ERRNO(clone(do_nothing, stack, CLONE_NEWNS | CLONE_FS, NULL),
+		   EINVAL);
+}
+END_TEST()
+
+FN_TEST(vfork)
+{
+	int status, fds[2];
+	char buf;
+	pid_t pid;
+
+	TEST_SUCC(pipe2(fds, O_NONBLOCK));
+
+	pid = TEST_SUCC(vfork());
+	if (pid == 0) {
+		usleep(100 * 1000);
+		if (write(fds[1], "a", 1) != 1)
+			_exit(1);
+		_exit(5);
+	}
+
+	// The parent is suspended until the child exits, so the data is
+	// already available.
+	TEST_RES(read(fds[0], &buf, 1), _ret == 1 && buf == 'a');
+	TEST_RES(waitpid(pid, &status, 0),
+		 _ret == pid && WIFEXITED(status) && WEXITSTATUS(status) == 5);
+
+	TEST_SUCC(close(fds[0]));
+	TEST_SUCC(close(fds[1]));
+}
+END_TEST()
+
+FN_TEST(clone_parent)
+{
+	int status, fds[2];
+	pid_t pid, grandchild;
+
+	TEST_SUCC(pipe(fds));
+
+	pid = TEST_SUCC(fork());
+	if (pid == 0) {
+		grandchild = clone(do_nothing,
+				   child_stack + sizeof(child_stack),
+				   CLONE_PARENT | SIGCHLD, NULL);
+		if (grandchild < 0)
+			_exit(1);
+		// The grandchild is a sibling, not a child.
+		if (waitpid(grandchild, NULL, 0) >= 0 || errno != ECHILD)
+			_exit(2);
+		if (write(fds[1], &grandchild, sizeof(grandchild)) !=
+		    sizeof(grandchild))
+			_exit(3);
+		_exit(0);
+	}
+
+	TEST_RES(waitpid(pid, &status, 0),
+		 _ret == pid && WIFEXITED(status) && WEXITSTATUS(status) == 0);
+	TEST_RES(read(fds[0], &grandchild, sizeof(grandchild)),
+		 _ret == sizeof(grandchild));
+	TEST_RES(waitpid(grandchild, &status, 0),
+		 _ret == grandchild && WIFEXITED(status) &&
+			 WEXITSTATUS(status) == 0);
+
+	TEST_SUCC(close(fds[0]));
+	TEST_SUCC(close(fds[1]));
+}
+END_TEST()
@@ -0,0 +1,264 @@
+// SPDX-License-Identifier: MPL-2.0
+
+#define _GNU_SOURCE
+
+#include "../network/test.h"
+
+#include <elf.h>
+#include <signal.h>
+#include <stddef.h>
+#include <sys/mman.h>
+#include <sys/ptrace.h>
+#include <sys/syscall.h>
+#include <sys/uio.h>
+#include <sys/user.h>
+#include <sys/wait.h>
+#include <unistd.h>
+
+#if defined(__x86_64__)
+#define SYSCALL_NR(regs) ((regs).orig_rax)
+#define SYSCALL_RET(regs) ((regs).rax)
+#elif defined(__riscv) && __riscv_xlen == 64
+#define SYSCALL_NR(regs) ((regs).a7)
+#define SYSCALL_RET(regs) ((regs).a0)
+#endif
+
+static int wait_status(pid_t pid)
+{
+	int status;
+
+	if (waitpid(pid, &status, 0) != pid)
+		return -1;
+
+	return status;
+}
+
+static int is_exited_with_zero(int status)
+{
+	return status >= 0 && WIFEXITED(status) && WEXITSTATUS(status) == 0;
+}
+
+static int is_killed_by(int status, int sig)
+{
+	return status >= 0 && WIFSIGNALED(status) && WTERMSIG(status) == sig;
+}
+
+static int is_stopped_by(int status, int sig)
+{
+	return status >= 0 && WIFSTOPPED(status) && WSTOPSIG(status) == sig;
+}
+
+static int get_regs(pid_t pid, struct user_regs_struct *regs)
+{
+	struct iovec iov = { .iov_base = regs, .iov_len = sizeof(*regs) };
+
+	if (ptrace(PTRACE_GETREGSET, pid, NT_PRSTATUS, &iov) < 0)
+		return -1;
+
+	return iov.iov_len == sizeof(*regs) ? 0 : -1;
+}
+
+static int set_regs(pid_t pid, struct user_regs_struct *regs)
+{
+	struct iovec iov = { .iov_base = regs, .iov_len = sizeof(*regs) };
+
+	return ptrace(PTRACE_SETREGSET, pid, NT_PRSTATUS, &iov);
+}
+
+static volatile long value = 1;
+
+FN_TEST(traceme)
+{
+	pid_t pid;
+
+	pid = TEST_SUCC(fork());
+	if (pid == 0) {
+		if (ptrace(PTRACE_TRACEME, 0, NULL, NULL) < 0)
+			_exit(1);
+		// A thread can only be traced by one tracer.
+		if (ptrace(PTRACE_TRACEME, 0, NULL, NULL) != -1 ||
+		    errno != EPERM)
+			_exit(1);
+		kill(getpid(), SIGSTOP);
+		_exit(value == 2 ? 0 : 1);
+	}
+	TEST_RES(wait_status(pid), is_stopped_by(_ret, SIGSTOP));
+
+	TEST_RES(ptrace(PTRACE_PEEKDATA, pid, &value, NULL), _ret == 1);
+	TEST_SUCC(ptrace(PTRACE_POKEDATA, pid, &value, (void *)2));
+	TEST_RES(ptrace(PTRACE_PEEKDATA, pid, &value, NULL), _ret == 2);
+	// The memory of the tracer is not changed.
+	TEST_RES(value, _ret == 1);
+
+	TEST_ERRNO(ptrace(PTRACE_PEEKDATA, pid, NULL, NULL), EIO);
+	TEST_ERRNO(ptrace(PTRACE_SETOPTIONS, pid, NULL, (void *)(1L << 30)),
+		   EINVAL);
+	TEST_ERRNO(ptrace(PTRACE_CONT, pid, NULL, (void *)1000), EIO);
+
+	TEST_SUCC(ptrace(PTRACE_CONT, pid, NULL, NULL));
+	TEST_RES(wait_status(pid), is_exited_with_zero(_ret));
+}
+END_TEST()
+
+FN_TEST(poke_readonly)
+{
+	volatile long *page;
+	pid_t pid;
+
+	page = mmap(NULL, getpagesize(), PROT_READ | PROT_WRITE,
+		    MAP_PRIVATE | MAP_ANONYMOUS, -1, 0);
+	TEST_RES(page == MAP_FAILED ? -1 : 0, _ret == 0);
+	*page = 1;
+	TEST_SUCC(mprotect((void *)page, getpagesize(), PROT_READ));
+
+	pid = TEST_SUCC(fork());
+	if (pid == 0) {
+		if (ptrace(PTRACE_TRACEME, 0, NULL, NULL) < 0)
+			_exit(1);
+		kill(getpid(), SIGSTOP);
+		_exit(*page == 2 ? 0 : 1);
+	}
+	TEST_RES(wait_status(pid), is_stopped_by(_ret, SIGSTOP));
+
+	// The tracer can write to the read-only pages, as debuggers do
+	// when they set breakpoints.
+	TEST_SUCC(ptrace(PTRACE_POKEDATA, pid, page, (void *)2));
+	TEST_RES(ptrace(PTRACE_PEEKDATA, pid, page, NULL), _ret == 2);
+	TEST_RES(*page, _ret == 1);
+
+	TEST_SUCC(ptrace(PTRACE_CONT, pid, NULL, NULL));
+	TEST_RES(wait_status(pid), is_exited_with_zero(_ret));
+
+	TEST_SUCC(munmap((void *)page, getpagesize()));
+}
+END_TEST()
+
+FN_TEST(syscall_stops)
+{
+	struct user_regs_struct regs;
+	pid_t pid;
+
+	pid = TEST_SUCC(fork());
+	if (pid == 0) {
+		if (ptrace(PTRACE_TRACEME, 0, NULL, NULL) < 0)
+			_exit(1);
+		kill(getpid(), SIGSTOP);
+		// The return value is changed by the tracer.
+		_exit(syscall(SYS_getppid) == 42 ? 0 : 1);
+	}
+	TEST_RES(wait_status(pid), is_stopped_by(_ret, SIGSTOP));
+	TEST_SUCC(ptrace(PTRACE_SETOPTIONS, pid, NULL,
+			 (void *)(PTRACE_O_TRACESYSGOOD | PTRACE_O_EXITKILL)));
+
+	// Syscall-entry-stop
+	TEST_SUCC(ptrace(PTRACE_SYSCALL, pid, NULL, NULL));
+	TEST_RES(wait_status(pid), is_stopped_by(_ret, SIGTRAP | 0x80));
+	TEST_RES(get_regs(pid, &regs), SYSCALL_NR(regs) == SYS_getppid);
+
+	// Syscall-exit-stop
+	TEST_SUCC(ptrace(PTRACE_SYSCALL, pid, NULL, NULL));
+	TEST_RES(wait_status(pid), is_stopped_by(_ret, SIGTRAP | 0x80));
+	TEST_RES(get_regs(pid, &regs), SYSCALL_NR(regs) == SYS_getppid &&
+					       SYSCALL_RET(regs) == getpid());
+	SYSCALL_RET(regs) = 42;
+	TEST_SUCC(set_regs(pid, &regs));
+
+	TEST_SUCC(ptrace(PTRACE_CONT, pid, NULL, NULL));
+	TEST_RES(wait_status(pid), is_exited_with_zero(_ret));
+}
+END_TEST()
+
+#ifdef __x86_64__
+FN_TEST(getregs)
+{
+	struct user_regs_struct regs, regset;
+	pid_t pid;
+
+	pid = TEST_SUCC(fork());
+	if (pid == 0) {
+		if (ptrace(PTRACE_TRACEME, 0, NULL, NULL) < 0)
+			_exit(1);
+		kill(getpid(), SIGSTOP);
+		_exit(0);
+	}
+	TEST_RES(wait_status(pid), is_stopped_by(_ret, SIGSTOP));
+
+	TEST_SUCC(ptrace(PTRACE_GETREGS, pid, NULL, &regs));
+	TEST_SUCC(get_regs(pid, &regset));
+	TEST_RES(regs.rip, _ret == regset.rip && _ret != 0);
+	TEST_RES(regs.rsp, _ret == regset.rsp && _ret != 0);
+	TEST_RES(ptrace(PTRACE_PEEKUSER, pid,
+			offsetof(struct user_regs_struct, rip), NULL),
+		 _ret == regs.rip);
+	TEST_ERRNO(ptrace(PTRACE_PEEKUSER, pid, 1, NULL), EIO);
+	TEST_SUCC(ptrace(PTRACE_SETREGS, pid, NULL, &regs));
+
+	TEST_SUCC(ptrace(PTRACE_CONT, pid, NULL, NULL));
+	TEST_RES(wait_status(pid), is_exited_with_zero(_ret));
+}
+END_TEST()
+#endif
+
+static volatile int sigusr2_count;
+
+static void handle_sigusr2(int sig)
+{
+	sigusr2_count++;
+}
+
+FN_TEST(signal_stops)
+{
+	siginfo_t siginfo;
+	pid_t pid;
+
+	pid = TEST_SUCC(fork());
+	if (pid == 0) {
+		if (ptrace(PTRACE_TRACEME, 0, NULL, NULL) < 0)
+			_exit(1);
+		signal(SIGUSR2, handle_sigusr2);
+		// The tracer replaces the signal with `SIGUSR2`.
+		kill(getpid(), SIGUSR1);
+		// The tracer discards the signal.
+		kill(getpid(), SIGUSR1);
+		_exit(sigusr2_count == 1 ? 0 : 1);
+	}
+
+	TEST_RES(wait_status(pid), is_stopped_by(_ret, SIGUSR1));
+	TEST_SUCC(ptrace(PTRACE_GETSIGINFO, pid, NULL, &siginfo));
+	TEST_RES(siginfo.si_signo, _ret == SIGUSR1);
+	// The injected signal is delivered without another stop.
+	TEST_SUCC(ptrace(PTRACE_CONT, pid, NULL, (void *)SIGUSR2));
+
+	TEST_RES(wait_status(pid), is_stopped_by(_ret, SIGUSR1));
+	TEST_SUCC(ptrace(PTRACE_CONT, pid, NULL, NULL));
+
+	TEST_RES(wait_status(pid), is_exited_with_zero(_ret));
+}
+END_TEST()
+
+FN_TEST(attach_detach)
+{
+	pid_t pid;
+
+	TEST_ERRNO(ptrace(PTRACE_ATTACH, getpid(), NULL, NULL), EPERM);
+
+	pid = TEST_SUCC(fork());
+	if (pid == 0) {
+		for (;;)
+			pause();
+	}
+
+	// The child is not traced.
+	TEST_ERRNO(ptrace(PTRACE_CONT, pid, NULL, NULL), ESRCH);
+
+	TEST_SUCC(ptrace(PTRACE_ATTACH, pid, NULL, NULL));
+	TEST_ERRNO(ptrace(PTRACE_ATTACH, pid, NULL, NULL), EPERM);
+	TEST_RES(wait_status(pid), is_stopped_by(_ret, SIGSTOP));
+	TEST_RES(ptrace(PTRACE_PEEKDATA, pid, &value, NULL), _ret == 1);
+	TEST_SUCC(ptrace(PTRACE_DETACH, pid, NULL, NULL));
+
+	TEST_ERRNO(ptrace(PTRACE_CONT, pid, NULL, NULL), ESRCH);
+	TEST_SUCC(kill(pid, SIGKILL));
+	TEST_RES(wait_status(pid), is_killed_by(_ret, SIGKILL));
+}
+END_TEST()
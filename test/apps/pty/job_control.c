@@ -0,0 +1,244 @@
+// SPDX-License-Identifier: MPL-2.0
+
+#define _GNU_SOURCE
+
+#include "../network/test.h"
+
+#include <pty.h>
+#include <signal.h>
+#include <sys/ioctl.h>
+#include <sys/wait.h>
+#include <termios.h>
+#include <unistd.h>
+
+static int master, slave;
+
+FN_SETUP(new_session)
+{
+	struct termios term;
+	int status;
+	pid_t pid;
+
+	// The tests run in a new session, so that the pseudo terminal can become the controlling
+	// terminal of the session.
+	pid = CHECK(fork());
+	if (pid != 0) {
+		CHECK_WITH(waitpid(pid, &status, 0),
+			   _ret == pid && WIFEXITED(status));
+		exit(WEXITSTATUS(status));
+	}
+
+	CHECK(setsid());
+	CHECK(openpty(&master, &slave, NULL, NULL, NULL));
+	CHECK(ioctl(slave, TIOCSCTTY, 0));
+
+	// Disable echo, so that only the data written to the slave can be read from the master.
+	CHECK(tcgetattr(slave, &term));
+	term.c_lflag &= ~ECHO;
+	CHECK(tcsetattr(slave, TCSANOW, &term));
+
+	// Like shells, ignore `SIGTTOU` to set the foreground process group from the background.
+	signal(SIGTTOU, SIG_IGN);
+}
+END_SETUP()
+
+FN_TEST(setsid_setpgid)
+{
+	char *argv[] = { "/bin/sh", "-c", "kill -STOP $$", NULL };
+	int pipefds[2];
+	int status;
+	pid_t pid;
+	char buf;
+
+	// A session leader cannot create a new session or join another process group.
+	TEST_ERRNO(setsid(), EPERM);
+	TEST_ERRNO(setpgid(0, 0), EPERM);
+	TEST_ERRNO(setpgid(0, -1), EINVAL);
+
+	// A child in another session cannot be moved.
+	CHECK(pipe(pipefds));
+	pid = TEST_SUCC(fork());
+	if (pid == 0) {
+		setsid();
+		write(pipefds[1], "", 1);
+		pause();
+		_exit(EXIT_FAILURE);
+	}
+	TEST_RES(read(pipefds[0], &buf, 1), _ret == 1);
+	TEST_ERRNO(setpgid(pid, pid), EPERM);
+	TEST_SUCC(kill(pid, SIGKILL));
+	TEST_RES(waitpid(pid, &status, 0), _ret == pid);
+	close(pipefds[0]);
+	close(pipefds[1]);
+
+	// A child that has called `execve` cannot be moved.
+	pid = TEST_SUCC(fork());
+	if (pid == 0) {
+		execv(argv[0], argv);
+		_exit(EXIT_FAILURE);
+	}
+	TEST_RES(waitpid(pid, &status, WUNTRACED),
+		 _ret == pid && WIFSTOPPED(status) &&
+			 WSTOPSIG(status) == SIGSTOP);
+	TEST_ERRNO(setpgid(pid, pid), EACCES);
+	TEST_SUCC(kill(pid, SIGKILL));
+	TEST_RES(waitpid(pid, &status, 0),
+		 _ret == pid && WIFSIGNALED(status) &&
+			 WTERMSIG(status) == SIGKILL);
+}
+END_TEST()
+
+FN_TEST(foreground)
+{
+	int other_master, other_slave;
+	int status;
+	pid_t pid;
+
+	TEST_RES(tcgetpgrp(slave), _ret == getpid());
+
+	// The terminal is not the controlling terminal.
+	CHECK(openpty(&other_master, &other_slave, NULL, NULL, NULL));
+	TEST_ERRNO(tcgetpgrp(other_slave), ENOTTY);
+	TEST_ERRNO(tcsetpgrp(other_slave, getpid()), ENOTTY);
+	close(other_master);
+	close(other_slave);
+
+	// The process group does not exist.
+	pid = TEST_SUCC(fork());
+	if (pid == 0)
+		_exit(EXIT_SUCCESS);
+	TEST_RES(waitpid(pid, &status, 0), _ret == pid);
+	TEST_ERRNO(tcsetpgrp(slave, pid), ESRCH);
+	TEST_RES(tcgetpgrp(slave), _ret == getpid());
+}
+END_TEST()
+
+FN_TEST(background_read)
+{
+	int status;
+	pid_t pid;
+	char buf;
+
+	// A background process group is stopped by `SIGTTIN` when reading from the terminal, and
+	// the read is restarted after the process group is continued.
+	pid = TEST_SUCC(fork());
+	if (pid == 0) {
+		setpgid(0, 0);
+		if (read(slave, &buf, 1) != 1 || buf != 'x')
+			_exit(EXIT_FAILURE);
+		_exit(EXIT_SUCCESS);
+	}
+	TEST_SUCC(setpgid(pid, pid));
+	TEST_RES(waitpid(pid, &status, WUNTRACED),
+		 _ret == pid && WIFSTOPPED(status) &&
+			 WSTOPSIG(status) == SIGTTIN);
+
+	TEST_SUCC(tcsetpgrp(slave, pid));
+	TEST_RES(write(master, "x\n", 2), _ret == 2);
+	TEST_SUCC(kill(pid, SIGCONT));
+	TEST_RES(waitpid(pid, &status, 0),
+		 _ret == pid && WIFEXITED(status) &&
+			 WEXITSTATUS(status) == EXIT_SUCCESS);
+	TEST_SUCC(tcsetpgrp(slave, getpid()));
+
+	// If `SIGTTIN` is ignored, the read fails instead.
+	pid = TEST_SUCC(fork());
+	if (pid == 0) {
+		setpgid(0, 0);
+		signal(SIGTTIN, SIG_IGN);
+		if (read(slave, &buf, 1) != -1 || errno != EIO)
+			_exit(EXIT_FAILURE);
+		_exit(EXIT_SUCCESS);
+	}
+	TEST_SUCC(setpgid(pid, pid));
+	TEST_RES(waitpid(pid, &status, 0),
+		 _ret == pid && WIFEXITED(status) &&
+			 WEXITSTATUS(status) == EXIT_SUCCESS);
+}
+END_TEST()
+
+FN_TEST(background_write)
+{
+	struct termios term;
+	int status;
+	pid_t pid;
+	char buf;
+
+	CHECK(tcgetattr(slave, &term));
+	term.c_lflag |= TOSTOP;
+	CHECK(tcsetattr(slave, TCSANOW, &term));
+
+	// With `TOSTOP`, a background process group is stopped by `SIGTTOU` when writing to the
+	// terminal.
+	pid = TEST_SUCC(fork());
+	if (pid == 0) {
+		setpgid(0, 0);
+		signal(SIGTTOU, SIG_DFL);
+		if (write(slave, "y", 1) != 1)
+			_exit(EXIT_FAILURE);
+		_exit(EXIT_SUCCESS);
+	}
+	TEST_SUCC(setpgid(pid, pid));
+	TEST_RES(waitpid(pid, &status, WUNTRACED),
+		 _ret == pid && WIFSTOPPED(status) &&
+			 WSTOPSIG(status) == SIGTTOU);
+
+	TEST_SUCC(tcsetpgrp(slave, pid));
+	TEST_SUCC(kill(pid, SIGCONT));
+	TEST_RES(waitpid(pid, &status, 0),
+		 _ret == pid && WIFEXITED(status) &&
+			 WEXITSTATUS(status) == EXIT_SUCCESS);
+	TEST_RES(read(master, &buf, 1), _ret == 1 && buf == 'y');
+	TEST_SUCC(tcsetpgrp(slave, getpid()));
+
+	// If `SIGTTOU` is ignored, the write succeeds.
+	pid = TEST_SUCC(fork());
+	if (pid == 0) {
+		setpgid(0, 0);
+		if (write(slave, "z", 1) != 1)
+			_exit(EXIT_FAILURE);
+		_exit(EXIT_SUCCESS);
+	}
+	TEST_SUCC(setpgid(pid, pid));
+	TEST_RES(waitpid(pid, &status, 0),
+		 _ret == pid && WIFEXITED(status) &&
+			 WEXITSTATUS(status) == EXIT_SUCCESS);
+	TEST_RES(read(master, &buf, 1), _ret == 1 && buf == 'z');
+
+	term.c_lflag &= ~TOSTOP;
+	CHECK(tcsetattr(slave, TCSANOW, &term));
+}
+END_TEST()
+
+FN_TEST(signal_chars)
+{
+	int status;
+	pid_t pid;
+	char buf;
+
+	pid = TEST_SUCC(fork());
+	if (pid == 0) {
+		setpgid(0, 0);
+		for (;;)
+			read(slave, &buf, 1);
+	}
+	TEST_SUCC(setpgid(pid, pid));
+	TEST_SUCC(tcsetpgrp(slave, pid));
+
+	// The suspend character stops the foreground process group.
+	TEST_RES(write(master, "\x1a", 1), _ret == 1);
+	TEST_RES(waitpid(pid, &status, WUNTRACED),
+		 _ret == pid && WIFSTOPPED(status) &&
+			 WSTOPSIG(status) == SIGTSTP);
+	TEST_SUCC(kill(pid, SIGCONT));
+	TEST_RES(waitpid(pid, &status, WCONTINUED),
+		 _ret == pid && WIFCONTINUED(status));
+
+	// The interrupt character terminates the foreground process group.
+	TEST_RES(write(master, "\x03", 1), _ret == 1);
+	TEST_RES(waitpid(pid, &status, 0),
+		 _ret == pid && WIFSIGNALED(status) &&
+			 WTERMSIG(status) == SIGINT);
+	TEST_SUCC(tcsetpgrp(slave, getpid()));
+}
+END_TEST()
@@ -0,0 +1,203 @@
+// SPDX-License-Identifier: MPL-2.0
+
+#define _GNU_SOURCE
+
+#include "../network/test.h"
+
+#include <fcntl.h>
+#include <linux/random.h>
+#include <stdio.h>
+#include <stdlib.h>
+#include <sys/ioctl.h>
+#include <sys/random.h>
+#include <sys/syscall.h>
+#include <sys/wait.h>
+#include <unistd.h>
+
+#ifndef GRND_INSECURE
+#define GRND_INSECURE 0x0004
+#endif
+
+static char buf1[4096 * 3 + 1];
+static char buf2[4096 * 3 + 1];
+
+static int is_all_zero(const char *buf, size_t len)
+{
+	size_t i;
+
+	for (i = 0; i < len; i++)
+		if (buf[i] != 0)
+			return 0;
+	return 1;
+}
+
+static int wait_status(pid_t pid)
+{
+	int status;
+
+	if (waitpid(pid, &status, 0) != pid)
+		return -1;
+
+	return status;
+}
+
+static int is_exited_with_zero(int status)
+{
+	return status >= 0 && WIFEXITED(status) && WEXITSTATUS(status) == 0;
+}
+
+FN_TEST(getrandom_flags)
+{
+	TEST_RES(getrandom(buf1, 64, 0), _ret == 64);
+	TEST_RES(getrandom(buf1, 64, GRND_NONBLOCK), _ret == 64);
+	TEST_RES(getrandom(buf1, 64, GRND_RANDOM), _ret == 64);
+	TEST_RES(getrandom(buf1, 64, GRND_INSECURE), _ret == 64);
+	TEST_RES(getrandom(buf1, 0, 0), _ret == 0);
+
+	TEST_ERRNO(getrandom(buf1, 64, 0x8), EINVAL);
+	TEST_ERRNO(getrandom(buf1, 64, GRND_INSECURE | GRND_RANDOM), EINVAL);
+}
+END_TEST()
+
+FN_TEST(getrandom_bytes)
+{
+	memset(buf1, 0, sizeof(buf1));
+	memset(buf2, 0, sizeof(buf2));
+
+	TEST_RES(getrandom(buf1, sizeof(buf1), 0), _ret == sizeof(buf1));
+	TEST_RES(getrandom(buf2, sizeof(buf2), 0), _ret == sizeof(buf2));
+
+	// Every page of the output should be filled, and the outputs should
+	// differ.
+	TEST_RES(0, !is_all_zero(buf1, 4096) &&
+			    !is_all_zero(buf1 + 4096 * 2, 4096 + 1));
+	TEST_RES(memcmp(buf1, buf2, sizeof(buf1)), _ret != 0);
+
+	TEST_ERRNO(syscall(SYS_getrandom, NULL, 16, 0), EFAULT);
+}
+END_TEST()
+
+FN_TEST(dev_random)
+{
+	int fd;
+
+	fd = TEST_SUCC(open("/dev/random", O_RDWR));
+	TEST_RES(read(fd, buf1, sizeof(buf1)), _ret == sizeof(buf1));
+	TEST_RES(write(fd, buf2, 100), _ret == 100);
+	TEST_SUCC(close(fd));
+
+	fd = TEST_SUCC(open("/dev/urandom", O_RDWR));
+	TEST_RES(read(fd, buf2, sizeof(buf2)), _ret == sizeof(buf2));
+	TEST_RES(memcmp(buf1, buf2, sizeof(buf1)), _ret != 0);
+	TEST_RES(write(fd, buf1, 100), _ret == 100);
+	TEST_SUCC(close(fd));
+}
+END_TEST()
+
+FN_TEST(ioctl)
+{
+	struct {
+		struct rand_pool_info info;
+		char buf[32];
+	} pool_info;
+	int fd, count;
+
+	fd = TEST_SUCC(open("/dev/urandom", O_RDONLY));
+
+	TEST_RES(ioctl(fd, RNDGETENTCNT, &count), _ret == 0 && count == 256);
+
+	count = -1;
+	TEST_ERRNO(ioctl(fd, RNDADDTOENTCNT, &count), EINVAL);
+	count = 8;
+	TEST_SUCC(ioctl(fd, RNDADDTOENTCNT, &count));
+
+	pool_info.info.entropy_count = 8;
+	pool_info.info.buf_size = sizeof(pool_info.buf);
+	memset(pool_info.buf, 0x5a, sizeof(pool_info.buf));
+	TEST_SUCC(ioctl(fd, RNDADDENTROPY, &pool_info));
+
+	TEST_SUCC(ioctl(fd, RNDZAPENTCNT));
+	TEST_SUCC(ioctl(fd, RNDCLEARPOOL));
+	TEST_RES(ioctl(fd, RNDGETENTCNT, &count), _ret == 0 && count == 256);
+
+	TEST_SUCC(ioctl(fd, RNDRESEEDCRNG));
+
+	TEST_SUCC(close(fd));
+}
+END_TEST()
+
+FN_TEST(ioctl_unprivileged)
+{
+	pid_t pid;
+
+	pid = TEST_SUCC(fork());
+	if (pid == 0) {
+		int fd = open("/dev/urandom", O_RDONLY);
+		int count = 8;
+
+		if (fd < 0 || setuid(65534) < 0)
+			_exit(1);
+		if (ioctl(fd, RNDGETENTCNT, &count) < 0)
+			_exit(2);
+		if (ioctl(fd, RNDADDTOENTCNT, &count) != -1 || errno != EPERM)
+			_exit(3);
+		if (ioctl(fd, RNDRESEEDCRNG) != -1 || errno != EPERM)
+			_exit(4);
+		_exit(0);
+	}
+	TEST_RES(wait_status(pid), is_exited_with_zero(_ret));
+}
+END_TEST()
+
+static int read_file(const char *path, char *buf, size_t len)
+{
+	int fd, ret;
+
+	fd = open(path, O_RDONLY);
+	if (fd < 0)
+		return -1;
+
+	ret = read(fd, buf, len - 1);
+	if (ret >= 0)
+		buf[ret] = '\0';
+	close(fd);
+
+	return ret;
+}
+
+static int is_uuid(const char *s)
+{
+	int i;
+
+	for (i = 0; i < 36; i++) {
+		if (i == 8 || i == 13 || i == 18 || i == 23) {
+			if (s[i] != '-')
+				return 0;
+		} else if (!strchr("0123456789abcdef", s[i]) || s[i] == '\0') {
+			return 0;
+		}
+	}
+	return s[14] == '4' && s[36] == '\n' && s[37] == '\0';
+}
+
+FN_TEST(procfs)
+{
+	char s1[64], s2[64];
+
+	TEST_RES(read_file("/proc/sys/kernel/random/poolsize", s1, sizeof(s1)),
+		 strcmp(s1, "256\n") == 0);
+	TEST_RES(read_file("/proc/sys/kernel/random/entropy_avail", s1,
+			   sizeof(s1)),
+		 strcmp(s1, "256\n") == 0);
+
+	TEST_RES(read_file("/proc/sys/kernel/random/boot_id", s1, sizeof(s1)),
+		 is_uuid(s1));
+	TEST_RES(read_file("/proc/sys/kernel/random/boot_id", s2, sizeof(s2)),
+		 strcmp(s1, s2) == 0);
+
+	TEST_RES(read_file("/proc/sys/kernel/random/uuid", s1, sizeof(s1)),
+		 is_uuid(s1));
+	TEST_RES(read_file("/proc/sys/kernel/random/uuid", s2, sizeof(s2)),
+		 is_uuid(s2) && strcmp(s1, s2) != 0);
+}
+END_TEST()
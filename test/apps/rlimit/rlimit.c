@@ -0,0 +1,283 @@
+// SPDX-License-Identifier: MPL-2.0
+
+#define _GNU_SOURCE
+
+#include "../network/test.h"
+
+#include <fcntl.h>
+#include <signal.h>
+#include <sys/mman.h>
+#include <sys/resource.h>
+#include <sys/syscall.h>
+#include <sys/wait.h>
+#include <unistd.h>
+
+#define TEST_FILE "/tmp/rlimit_test_file"
+
+static struct rlimit old_nofile;
+
+static int set_soft_limit(int resource, rlim_t limit)
+{
+	struct rlimit rlimit;
+
+	if (getrlimit(resource, &rlimit) < 0)
+		return -1;
+	rlimit.rlim_cur = limit;
+
+	return setrlimit(resource, &rlimit);
+}
+
+FN_SETUP(save_limits)
+{
+	CHECK(getrlimit(RLIMIT_NOFILE, &old_nofile));
+}
+END_SETUP()
+
+FN_TEST(invalid_limits)
+{
+	struct rlimit rlimit = { .rlim_cur = 2, .rlim_max = 1 };
+
+	TEST_ERRNO(setrlimit(RLIMIT_NOFILE, &rlimit), EINVAL);
+	TEST_ERRNO(prlimit(0, RLIMIT_NOFILE, &rlimit, NULL), EINVAL);
+
+	// The hard limit of `RLIMIT_NOFILE` cannot exceed `nr_open`.
+	rlimit.rlim_cur = 1024;
+	rlimit.rlim_max = RLIM_INFINITY;
+	TEST_ERRNO(setrlimit(RLIMIT_NOFILE, &rlimit), EPERM);
+
+	TEST_ERRNO(prlimit(0x3fffffff, RLIMIT_NOFILE, NULL, &rlimit), ESRCH);
+}
+END_TEST()
+
+FN_TEST(nofile)
+{
+	int fds[2];
+	int i;
+
+	TEST_SUCC(set_soft_limit(RLIMIT_NOFILE, 16));
+
+	while (open("/dev/null", O_RDONLY) >= 0)
+		;
+	TEST_ERRNO(open("/dev/null", O_RDONLY), EMFILE);
+	TEST_ERRNO(dup(0), EMFILE);
+	TEST_ERRNO(fcntl(0, F_DUPFD, 0), EMFILE);
+	TEST_ERRNO(fcntl(0, F_DUPFD, 16), EINVAL);
+	TEST_ERRNO(dup2(0, 16), EBADF);
+
+	// Only one file descriptor is free, so `pipe` fails without leaking it.
+	TEST_SUCC(close(15));
+	TEST_ERRNO(pipe(fds), EMFILE);
+	TEST_RES(dup(0), _ret == 15);
+	TEST_SUCC(close(15));
+	TEST_SUCC(close(14));
+	TEST_RES(pipe(fds), fds[0] == 14 && fds[1] == 15);
+
+	for (i = 3; i < 16; i++)
+		close(i);
+	TEST_SUCC(setrlimit(RLIMIT_NOFILE, &old_nofile));
+}
+END_TEST()
+
+FN_TEST(inherit)
+{
+	struct rlimit rlimit;
+	int status;
+	pid_t pid;
+
+	TEST_SUCC(set_soft_limit(RLIMIT_NOFILE, 64));
+
+	pid = TEST_SUCC(fork());
+	if (pid == 0) {
+		if (getrlimit(RLIMIT_NOFILE, &rlimit) < 0 ||
+		    rlimit.rlim_cur != 64)
+			_exit(EXIT_FAILURE);
+		pause();
+		_exit(EXIT_SUCCESS);
+	}
+
+	TEST_RES(prlimit(pid, RLIMIT_NOFILE, NULL, &rlimit),
+		 rlimit.rlim_cur == 64);
+	rlimit.rlim_cur = 32;
+	TEST_SUCC(prlimit(pid, RLIMIT_NOFILE, &rlimit, NULL));
+	TEST_RES(prlimit(pid, RLIMIT_NOFILE, NULL, &rlimit),
+		 rlimit.rlim_cur == 32);
+
+	TEST_SUCC(kill(pid, SIGKILL));
+	TEST_RES(waitpid(pid, &status, 0),
+		 _ret == pid && WIFSIGNALED(status) &&
+			 WTERMSIG(status) == SIGKILL);
+
+	TEST_SUCC(setrlimit(RLIMIT_NOFILE, &old_nofile));
+}
+END_TEST()
+
+static volatile sig_atomic_t num_sigxfsz;
+
+static void handle_sigxfsz(int sig)
+{
+	num_sigxfsz++;
+}
+
+FN_TEST(fsize)
+{
+	static char buf[8192];
+	struct sigaction action = { .sa_handler = handle_sigxfsz };
+	int fd;
+
+	TEST_SUCC(sigaction(SIGXFSZ, &action, NULL));
+	fd = TEST_SUCC(open(TEST_FILE, O_RDWR | O_CREAT | O_TRUNC, 0600));
+	TEST_SUCC(set_soft_limit(RLIMIT_FSIZE, 4096));
+
+	// The write is truncated at the limit.
+	TEST_RES(write(fd, buf, sizeof(buf)), _ret == 4096 && num_sigxfsz == 0);
+	TEST_ERRNO(write(fd, buf, sizeof(buf)), EFBIG);
+	TEST_RES(num_sigxfsz, _ret == 1);
+	TEST_ERRNO(pwrite(fd, buf, 1, 4096), EFBIG);
+	TEST_RES(pwrite(fd, buf, sizeof(buf), 1024), _ret == 3072);
+	TEST_ERRNO(ftruncate(fd, 8192), EFBIG);
+	TEST_RES(num_sigxfsz, _ret == 3);
+	TEST_SUCC(ftruncate(fd, 4096));
+
+	TEST_SUCC(set_soft_limit(RLIMIT_FSIZE, RLIM_INFINITY));
+	TEST_RES(write(fd, buf, sizeof(buf)), _ret == sizeof(buf));
+
+	TEST_SUCC(close(fd));
+	TEST_SUCC(unlink(TEST_FILE));
+	action.sa_handler = SIG_DFL;
+	TEST_SUCC(sigaction(SIGXFSZ, &action, NULL));
+}
+END_TEST()
+
+FN_TEST(address_space)
+{
+	const size_t limit = 1UL << 30;
+	const size_t size = 1UL << 20;
+	void *addr;
+
+	TEST_SUCC(set_soft_limit(RLIMIT_AS, limit));
+
+	TEST_ERRNO((long)mmap(NULL, 2 * limit, PROT_NONE,
+			      MAP_PRIVATE | MAP_ANONYMOUS, -1, 0),
+		   ENOMEM);
+	addr = (void *)TEST_RES((long)mmap(NULL, size, PROT_READ | PROT_WRITE,
+					   MAP_PRIVATE | MAP_ANONYMOUS, -1, 0),
+				_ret != (long)MAP_FAILED);
+
+	// Replacing the existing mapping does not grow the address space.
+	TEST_RES((long)mmap(addr, size, PROT_READ,
+			    MAP_PRIVATE | MAP_ANONYMOUS | MAP_FIXED, -1, 0),
+		 _ret == (long)addr);
+	TEST_SUCC(munmap(addr, size));
+
+	TEST_SUCC(set_soft_limit(RLIMIT_AS, RLIM_INFINITY));
+}
+END_TEST()
+
+FN_TEST(data)
+{
+	const size_t size = 16UL << 20;
+	unsigned long brk;
+
+	brk = TEST_SUCC(syscall(SYS_brk, 0));
+	TEST_SUCC(set_soft_limit(RLIMIT_DATA, size / 2));
+
+	// The program break does not change if the limit is exceeded.
+	TEST_RES(syscall(SYS_brk, brk + size), _ret == brk);
+
+	TEST_SUCC(set_soft_limit(RLIMIT_DATA, RLIM_INFINITY));
+	TEST_RES(syscall(SYS_brk, brk + size), _ret == brk + size);
+	TEST_RES(syscall(SYS_brk, brk), _ret == brk || _ret == brk + size);
+}
+END_TEST()
+
+static int pipe_fds[2];
+
+static void handle_sigxcpu(int sig)
+{
+	write(pipe_fds[1], "x", 1);
+}
+
+FN_TEST(cpu)
+{
+	struct rlimit rlimit = { .rlim_cur = 1, .rlim_max = 2 };
+	char buf[16];
+	int status;
+	pid_t pid;
+
+	TEST_SUCC(pipe(pipe_fds));
+
+	pid = TEST_SUCC(fork());
+	if (pid == 0) {
+		signal(SIGXCPU, handle_sigxcpu);
+		if (setrlimit(RLIMIT_CPU, &rlimit) < 0)
+			_exit(EXIT_FAILURE);
+		for (;;)
+			;
+	}
+
+	// The child receives `SIGXCPU` at the soft limit, and `SIGKILL` at the hard limit.
+	TEST_SUCC(close(pipe_fds[1]));
+	TEST_RES(waitpid(pid, &status, 0),
+		 _ret == pid && WIFSIGNALED(status) &&
+			 WTERMSIG(status) == SIGKILL);
+	TEST_RES(read(pipe_fds[0], buf, sizeof(buf)), _ret >= 1);
+	TEST_SUCC(close(pipe_fds[0]));
+}
+END_TEST()
+
+// Reads the line that starts with `name` in `/proc/self/limits`.
+static int read_limits_line(const char *name, char *line, size_t size)
+{
+	static char buf[4096];
+	char *start, *end;
+	ssize_t len;
+	int fd;
+
+	fd = open("/proc/self/limits", O_RDONLY);
+	if (fd < 0)
+		return -1;
+	len = read(fd, buf, sizeof(buf) - 1);
+	close(fd);
+	if (len < 0)
+		return -1;
+	buf[len] = 0;
+
+	for (start = buf; *start != 0; start = end + 1) {
+		end = strchr(start, '\n');
+		if (end == NULL)
+			return -1;
+		if (strncmp(start, name, strlen(name)) == 0 &&
+		    end - start + 2 <= size) {
+			memcpy(line, start, end - start + 1);
+			line[end - start + 1] = 0;
+			return 0;
+		}
+	}
+
+	return -1;
+}
+
+FN_TEST(proc_limits)
+{
+	char line[128];
+	char expected[128];
+
+	snprintf(expected, sizeof(expected), "%-25s %-20s %-20s %-10s\n",
+		 "Limit", "Soft Limit", "Hard Limit", "Units");
+	TEST_RES(read_limits_line("Limit", line, sizeof(line)),
+		 strcmp(line, expected) == 0);
+
+	TEST_SUCC(set_soft_limit(RLIMIT_NOFILE, 100));
+	snprintf(expected, sizeof(expected), "%-25s %-20d %-20lu %-10s\n",
+		 "Max open files", 100, old_nofile.rlim_max, "files");
+	TEST_RES(read_limits_line("Max open files", line, sizeof(line)),
+		 strcmp(line, expected) == 0);
+	TEST_SUCC(setrlimit(RLIMIT_NOFILE, &old_nofile));
+
+	TEST_RES(read_limits_line("Max address space", line, sizeof(line)),
+		 strstr(line, "unlimited") != NULL);
+	// The priority limits have no units.
+	TEST_RES(read_limits_line("Max nice priority", line, sizeof(line)),
+		 strlen(line) == 25 + 1 + 20 + 1 + 20 + 1 + 1);
+}
+END_TEST()
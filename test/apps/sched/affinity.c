@@ -0,0 +1,98 @@
+// SPDX-License-Identifier: MPL-2.0
+
+#define _GNU_SOURCE
+
+#include "../network/test.h"
+
+#include <fcntl.h>
+#include <sched.h>
+#include <string.h>
+#include <sys/syscall.h>
+#include <sys/wait.h>
+#include <unistd.h>
+
+static cpu_set_t old_mask;
+
+static int is_only_cpu0(cpu_set_t *mask)
+{
+	return CPU_COUNT(mask) == 1 && CPU_ISSET(0, mask);
+}
+
+FN_SETUP(save_mask)
+{
+	CHECK(sched_getaffinity(0, sizeof(old_mask), &old_mask));
+}
+END_SETUP()
+
+FN_TEST(invalid_args)
+{
+	cpu_set_t mask;
+
+	CPU_ZERO(&mask);
+	TEST_ERRNO(sched_setaffinity(0, sizeof(mask), &mask), EINVAL);
+	TEST_ERRNO(syscall(SYS_sched_setaffinity, 0, 0, &mask), EINVAL);
+	TEST_ERRNO(syscall(SYS_sched_getaffinity, 0, 0, &mask), EINVAL);
+
+	CPU_SET(0, &mask);
+	TEST_ERRNO(sched_setaffinity(0x7fffffff, sizeof(mask), &mask), ESRCH);
+	TEST_ERRNO(sched_getaffinity(0x7fffffff, sizeof(mask), &mask), ESRCH);
+}
+END_TEST()
+
+FN_TEST(migrate)
+{
+	cpu_set_t mask;
+
+	CPU_ZERO(&mask);
+	CPU_SET(0, &mask);
+	TEST_SUCC(sched_setaffinity(0, sizeof(mask), &mask));
+
+	// The thread is moved to the only allowed CPU.
+	TEST_RES(sched_getcpu(), _ret == 0);
+	TEST_RES(sched_getaffinity(0, sizeof(mask), &mask),
+		 is_only_cpu0(&mask));
+}
+END_TEST()
+
+FN_TEST(inherit)
+{
+	int status;
+	pid_t pid;
+
+	// The affinity is inherited by the child.
+	pid = TEST_SUCC(fork());
+	if (pid == 0) {
+		cpu_set_t mask;
+
+		if (sched_getaffinity(0, sizeof(mask), &mask) < 0)
+			_exit(1);
+		if (!is_only_cpu0(&mask))
+			_exit(2);
+		if (sched_getcpu() != 0)
+			_exit(3);
+		_exit(0);
+	}
+
+	TEST_RES(waitpid(pid, &status, 0),
+		 _ret == pid && WIFEXITED(status) && WEXITSTATUS(status) == 0);
+}
+END_TEST()
+
+FN_TEST(proc_status)
+{
+	char buf[4096];
+	int fd, len;
+
+	fd = TEST_SUCC(open("/proc/self/status", O_RDONLY));
+	len = TEST_SUCC(read(fd, buf, sizeof(buf) - 1));
+	buf[len] = '\0';
+	TEST_RES(0, strstr(buf, "\nCpus_allowed_list:\t0\n") != NULL);
+	TEST_SUCC(close(fd));
+}
+END_TEST()
+
+FN_SETUP(restore_mask)
+{
+	CHECK(sched_setaffinity(0, sizeof(old_mask), &old_mask));
+}
+END_SETUP()
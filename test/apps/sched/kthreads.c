@@ -0,0 +1,28 @@
+// SPDX-License-Identifier: MPL-2.0
+
+#define _GNU_SOURCE
+
+#include <fcntl.h>
+#include <string.h>
+#include <unistd.h>
+
+#include "../network/test.h"
+
+static char buf[16 * 1024];
+
+FN_TEST(list_kthreads)
+{
+	int fd;
+	ssize_t len;
+
+	fd = TEST_SUCC(open("/proc/kthreads", O_RDONLY));
+	len = TEST_RES(read(fd, buf, sizeof(buf) - 1), _ret > 0);
+	buf[len] = '\0';
+	TEST_SUCC(close(fd));
+
+	// The hung task detector always runs, and it is never parked.
+	TEST_RES(0, strstr(buf, "khungtaskd\tR\n") != NULL);
+	// Each line ends with a newline.
+	TEST_RES(0, buf[len - 1] == '\n');
+}
+END_TEST()
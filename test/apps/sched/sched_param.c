@@ -0,0 +1,167 @@
+// SPDX-License-Identifier: MPL-2.0
+
+#include "../network/test.h"
+
+#include <pthread.h>
+#include <sched.h>
+#include <sys/syscall.h>
+#include <sys/wait.h>
+#include <unistd.h>
+
+FN_TEST(priority_range)
+{
+	TEST_RES(sched_get_priority_max(SCHED_FIFO), _ret == 99);
+	TEST_RES(sched_get_priority_min(SCHED_FIFO), _ret == 1);
+	TEST_RES(sched_get_priority_max(SCHED_RR), _ret == 99);
+	TEST_RES(sched_get_priority_min(SCHED_RR), _ret == 1);
+	TEST_RES(sched_get_priority_max(SCHED_OTHER), _ret == 0);
+	TEST_RES(sched_get_priority_min(SCHED_OTHER), _ret == 0);
+
+	TEST_ERRNO(sched_get_priority_max(-1), EINVAL);
+	TEST_ERRNO(sched_get_priority_min(100), EINVAL);
+}
+END_TEST()
+
+FN_TEST(default_policy)
+{
+	struct sched_param param = { .sched_priority = -1 };
+
+	TEST_RES(sched_getscheduler(0), _ret == SCHED_OTHER);
+	TEST_RES(sched_getparam(0, &param), param.sched_priority == 0);
+	TEST_RES(sched_getscheduler(getpid()), _ret == SCHED_OTHER);
+}
+END_TEST()
+
+FN_TEST(invalid_args)
+{
+	struct sched_param param = { .sched_priority = 0 };
+
+	TEST_ERRNO(sched_getscheduler(-1), EINVAL);
+	TEST_ERRNO(sched_getscheduler(0x7fffffff), ESRCH);
+	TEST_ERRNO(syscall(SYS_sched_getparam, 0, NULL), EINVAL);
+	TEST_ERRNO(syscall(SYS_sched_setscheduler, 0, SCHED_OTHER, NULL),
+		   EINVAL);
+
+	TEST_ERRNO(sched_setscheduler(0, 100, &param), EINVAL);
+
+	param.sched_priority = 1;
+	TEST_ERRNO(sched_setscheduler(0, SCHED_OTHER, &param), EINVAL);
+
+	param.sched_priority = 0;
+	TEST_ERRNO(sched_setscheduler(0, SCHED_FIFO, &param), EINVAL);
+
+	param.sched_priority = 100;
+	TEST_ERRNO(sched_setscheduler(0, SCHED_RR, &param), EINVAL);
+}
+END_TEST()
+
+FN_TEST(real_time)
+{
+	struct sched_param param;
+	int status;
+	pid_t pid;
+
+	param.sched_priority = 10;
+	TEST_SUCC(sched_setscheduler(0, SCHED_FIFO, &param));
+	TEST_RES(sched_getscheduler(0), _ret == SCHED_FIFO);
+	TEST_RES(sched_getparam(0, &param), param.sched_priority == 10);
+
+	param.sched_priority = 20;
+	TEST_SUCC(sched_setparam(0, &param));
+	TEST_RES(sched_getscheduler(0), _ret == SCHED_FIFO);
+	TEST_RES(sched_getparam(0, &param), param.sched_priority == 20);
+
+	param.sched_priority = 30;
+	TEST_SUCC(sched_setscheduler(0, SCHED_RR, &param));
+	TEST_RES(sched_getscheduler(0), _ret == SCHED_RR);
+	TEST_RES(sched_getparam(0, &param), param.sched_priority == 30);
+
+	// The policy is inherited by the child.
+	pid = TEST_SUCC(fork());
+	if (pid == 0)
+		_exit(sched_getscheduler(0));
+	TEST_RES(waitpid(pid, &status, 0),
+		 _ret == pid && WIFEXITED(status) &&
+			 WEXITSTATUS(status) == SCHED_RR);
+
+	// The priority of the normal policy must be zero.
+	TEST_ERRNO(sched_setscheduler(0, SCHED_OTHER, &param), EINVAL);
+
+	param.sched_priority = 0;
+	TEST_SUCC(sched_setscheduler(0, SCHED_OTHER, &param));
+	TEST_RES(sched_getscheduler(0), _ret == SCHED_OTHER);
+	TEST_RES(sched_getparam(0, &param), param.sched_priority == 0);
+}
+END_TEST()
+
+FN_TEST(rr_interval)
+{
+	struct sched_param param = { .sched_priority = 10 };
+	struct timespec ts;
+
+	TEST_SUCC(sched_setscheduler(0, SCHED_RR, &param));
+	TEST_RES(sched_rr_get_interval(0, &ts),
+		 ts.tv_sec > 0 || ts.tv_nsec > 0);
+
+	TEST_SUCC(sched_setscheduler(0, SCHED_FIFO, &param));
+	TEST_RES(sched_rr_get_interval(0, &ts),
+		 ts.tv_sec == 0 && ts.tv_nsec == 0);
+
+	TEST_ERRNO(sched_rr_get_interval(-1, &ts), EINVAL);
+
+	param.sched_priority = 0;
+	TEST_SUCC(sched_setscheduler(0, SCHED_OTHER, &param));
+}
+END_TEST()
+
+static pthread_mutex_t pi_mutex;
+static volatile int pi_locked;
+
+static void *lock_pi_mutex(void *arg)
+{
+	(void)arg;
+
+	if (pthread_mutex_lock(&pi_mutex) != 0)
+		return (void *)1;
+	pi_locked = 1;
+	if (pthread_mutex_unlock(&pi_mutex) != 0)
+		return (void *)2;
+
+	return NULL;
+}
+
+FN_TEST(pi_mutex)
+{
+	struct sched_param param = { .sched_priority = 10 };
+	pthread_mutexattr_t attr;
+	pthread_attr_t thread_attr;
+	pthread_t thread;
+	void *ret;
+
+	TEST_SUCC(pthread_mutexattr_init(&attr));
+	TEST_SUCC(pthread_mutexattr_setprotocol(&attr, PTHREAD_PRIO_INHERIT));
+	TEST_SUCC(pthread_mutex_init(&pi_mutex, &attr));
+	TEST_SUCC(pthread_mutex_lock(&pi_mutex));
+
+	// Start a real-time thread that waits for the mutex.
+	TEST_SUCC(pthread_attr_init(&thread_attr));
+	TEST_SUCC(pthread_attr_setinheritsched(&thread_attr,
+					       PTHREAD_EXPLICIT_SCHED));
+	TEST_SUCC(pthread_attr_setschedpolicy(&thread_attr, SCHED_FIFO));
+	TEST_SUCC(pthread_attr_setschedparam(&thread_attr, &param));
+	TEST_SUCC(pthread_create(&thread, &thread_attr, lock_pi_mutex, NULL));
+	usleep(100 * 1000);
+
+	// The inherited priority does not change the policy of the owner.
+	TEST_RES(sched_getscheduler(0), _ret == SCHED_OTHER);
+	TEST_RES(pi_locked, _ret == 0);
+
+	TEST_SUCC(pthread_mutex_unlock(&pi_mutex));
+	TEST_RES(pthread_join(thread, &ret), _ret == 0 && ret == NULL);
+	TEST_RES(pi_locked, _ret == 1);
+
+	TEST_SUCC(pthread_attr_destroy(&thread_attr));
+	TEST_SUCC(pthread_mutex_destroy(&pi_mutex));
+	TEST_SUCC(pthread_mutexattr_destroy(&attr));
+}
+END_TEST()
@@ -57,11 +57,22 @@ echo "Start ext2 fs test......"
 test_ext2 "/ext2" "test_file.txt"
 echo "All ext2 fs test passed."
 
+echo "Start direct I/O test......"
+direct_io/direct_io
+echo "All direct I/O test passed."
+
+echo "Start fscrypt test......"
+fscrypt/fscrypt
+echo "All fscrypt test passed."
+
 echo "Start fdatasync test......"
 test_fdatasync
 echo "All fdatasync test passed."
 
 pipe/pipe_err
 pipe/short_rw
+pipe/splice
+pipe/pipe_ext
 epoll/epoll_err
 epoll/poll_err
+io_uring/io_uring
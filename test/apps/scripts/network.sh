@@ -33,5 +33,16 @@ sleep 0.2
 ./tcp_poll
 ./udp_err
 ./unix_err
+./unix_ancillary
+./netlink_route
+./packet_socket
+./ipv6
+./raw_socket
+./multicast
+./zerocopy
+./netfilter
+./tun
+./reuseport
+./proc_net
 
 echo "All network test passed"
@@ -10,29 +10,85 @@ cd ${SCRIPT_DIR}/..
 echo "Start process test......"
 # These test programs are sorted by name.
 tests="
+arch_prctl/arch_prctl
+audit/audit
+binfmt_misc/binfmt_misc
+capability/capset
+cgroup/cgroup
+clock/clock
+clock/ptp
+clock/tickless
+clock/vdso
 clone3/clone_exit_signal
 clone3/clone_no_exit_signal
+clone3/clone_pidfd
 clone3/clone_process
 cpu_affinity/cpu_affinity
 execve/execve
+execve/execve_mt
 exit/exit_code
 exit/exit_procfs
+exit/waitid
 eventfd2/eventfd2
+eventfd2/eventfd_semantics
 fork/fork
 fork_c/fork
+fork_c/vfork
 getpid/getpid
 hello_pie/hello
 hello_world/hello_world
+ia32/ia32
+ia32/ia32_libc
+itimer/posix_timer
 itimer/setitimer
 itimer/timer_create
+itimer/timerfd
+keyring/keyring
+kmsg/kmsg
+landlock/landlock
 mmap/mmap_and_fork
+mmap/mmap_anonymous
 mmap/mmap_shared_filebacked
 mmap/mmap_readahead
+mmap/mremap
+mmap/msync
+mmap/swapon
+mmap/oom_score_adj
+mmap/userfaultfd
+mmap/madvise
+mmap/ksm
+mmap/mlock
+mmap/pagemap
+mmap/mempolicy
+mmap/aslr
+mmap/wx
+mmap/thp
+mqueue/mqueue
+namespace/mount_ns
+namespace/pid_ns
+namespace/uts_ipc_ns
+pthread/futex
 pthread/pthread_test
+pthread/thread_group
+ptrace/ptrace
+pty/job_control
 pty/open_pty
+random/random
+rlimit/rlimit
+sched/affinity
+sched/kthreads
+sched/sched_param
+seccomp/seccomp
 shm/posix_shm
+signal_c/coredump
 signal_c/parent_death_signal
+signal_c/rt_signal
 signal_c/signal_test
+signal_c/signalfd
+static_pie/static_pie
+sysv_ipc/msg
+sysv_ipc/sem_undo
+sysv_ipc/shm
 "
 
 for testcase in ${tests}
@@ -0,0 +1,289 @@
+// SPDX-License-Identifier: MPL-2.0
+
+#define _GNU_SOURCE
+
+#include "../network/test.h"
+
+#include <linux/audit.h>
+#include <linux/filter.h>
+#include <linux/seccomp.h>
+#include <signal.h>
+#include <stddef.h>
+#include <sys/prctl.h>
+#include <sys/syscall.h>
+#include <sys/wait.h>
+#include <unistd.h>
+
+#if defined(__x86_64__)
+#define ARCH AUDIT_ARCH_X86_64
+#elif defined(__riscv) && __riscv_xlen == 64
+#define ARCH AUDIT_ARCH_RISCV64
+#endif
+
+#ifndef SYS_SECCOMP
+#define SYS_SECCOMP 1
+#endif
+
+#define ARRAY_LEN(array) (sizeof(array) / sizeof((array)[0]))
+
+static int wait_status(pid_t pid)
+{
+	int status;
+
+	if (waitpid(pid, &status, 0) != pid)
+		return -1;
+
+	return status;
+}
+
+static int is_exited_with_zero(int status)
+{
+	return status >= 0 && WIFEXITED(status) && WEXITSTATUS(status) == 0;
+}
+
+static int is_killed_by(int status, int sig)
+{
+	return status >= 0 && WIFSIGNALED(status) && WTERMSIG(status) == sig;
+}
+
+static int install_filter_with_flags(unsigned int flags, int nr,
+				     unsigned int ret)
+{
+	struct sock_filter insns[] = {
+		BPF_STMT(BPF_LD | BPF_W | BPF_ABS,
+			 offsetof(struct seccomp_data, arch)),
+		BPF_JUMP(BPF_JMP | BPF_JEQ | BPF_K, ARCH, 1, 0),
+		BPF_STMT(BPF_RET | BPF_K, SECCOMP_RET_KILL_PROCESS),
+		BPF_STMT(BPF_LD | BPF_W | BPF_ABS,
+			 offsetof(struct seccomp_data, nr)),
+		BPF_JUMP(BPF_JMP | BPF_JEQ | BPF_K, nr, 0, 1),
+		BPF_STMT(BPF_RET | BPF_K, ret),
+		BPF_STMT(BPF_RET | BPF_K, SECCOMP_RET_ALLOW),
+	};
+	struct sock_fprog prog = {
+		.len = ARRAY_LEN(insns),
+		.filter = insns,
+	};
+
+	return syscall(SYS_seccomp, SECCOMP_SET_MODE_FILTER, flags, &prog);
+}
+
+static int install_filter(int nr, unsigned int ret)
+{
+	return install_filter_with_flags(0, nr, ret);
+}
+
+FN_TEST(no_new_privs)
+{
+	pid_t pid;
+
+	TEST_ERRNO(prctl(PR_SET_NO_NEW_PRIVS, 2, 0, 0, 0), EINVAL);
+	TEST_ERRNO(prctl(PR_GET_NO_NEW_PRIVS, 1, 0, 0, 0), EINVAL);
+
+	pid = TEST_SUCC(fork());
+	if (pid == 0) {
+		if (prctl(PR_GET_NO_NEW_PRIVS, 0, 0, 0, 0) != 0)
+			_exit(1);
+		if (prctl(PR_SET_NO_NEW_PRIVS, 1, 0, 0, 0) < 0)
+			_exit(1);
+		if (prctl(PR_GET_NO_NEW_PRIVS, 0, 0, 0, 0) != 1)
+			_exit(1);
+		_exit(0);
+	}
+	TEST_RES(wait_status(pid), is_exited_with_zero(_ret));
+}
+END_TEST()
+
+FN_TEST(invalid_args)
+{
+	struct sock_filter insns[] = {
+		// Unaligned loads are not allowed.
+		BPF_STMT(BPF_LD | BPF_W | BPF_ABS, 1),
+		BPF_STMT(BPF_RET | BPF_K, SECCOMP_RET_ALLOW),
+	};
+	struct sock_fprog prog = {
+		.len = ARRAY_LEN(insns),
+		.filter = insns,
+	};
+	unsigned int action;
+
+	TEST_ERRNO(syscall(SYS_seccomp, SECCOMP_SET_MODE_FILTER, 0, &prog),
+		   EINVAL);
+	insns[0].k = sizeof(struct seccomp_data);
+	TEST_ERRNO(syscall(SYS_seccomp, SECCOMP_SET_MODE_FILTER, 0, &prog),
+		   EINVAL);
+
+	TEST_ERRNO(syscall(SYS_seccomp, SECCOMP_SET_MODE_FILTER, 1 << 30,
+			   &prog),
+		   EINVAL);
+	TEST_ERRNO(syscall(SYS_seccomp, SECCOMP_SET_MODE_STRICT, 1, NULL),
+		   EINVAL);
+	TEST_ERRNO(syscall(SYS_seccomp, 100, 0, NULL), EINVAL);
+
+	action = SECCOMP_RET_ERRNO;
+	TEST_SUCC(syscall(SYS_seccomp, SECCOMP_GET_ACTION_AVAIL, 0, &action));
+	action = 0x12340000;
+	TEST_ERRNO(syscall(SYS_seccomp, SECCOMP_GET_ACTION_AVAIL, 0, &action),
+		   EOPNOTSUPP);
+
+	TEST_RES(prctl(PR_GET_SECCOMP), _ret == 0);
+}
+END_TEST()
+
+FN_TEST(ret_errno)
+{
+	pid_t pid;
+
+	pid = TEST_SUCC(fork());
+	if (pid == 0) {
+		if (prctl(PR_SET_NO_NEW_PRIVS, 1, 0, 0, 0) < 0)
+			_exit(1);
+		if (install_filter(SYS_getppid, SECCOMP_RET_ERRNO | EPERM) < 0)
+			_exit(1);
+		if (prctl(PR_GET_SECCOMP) != 2)
+			_exit(1);
+		if (syscall(SYS_getppid) != -1 || errno != EPERM)
+			_exit(1);
+		// Other system calls are allowed.
+		if (syscall(SYS_getpid) != getpid())
+			_exit(1);
+
+		// The filters are inherited by the child processes.
+		pid = fork();
+		if (pid == 0) {
+			if (syscall(SYS_getppid) != -1 || errno != EPERM)
+				_exit(1);
+			_exit(0);
+		}
+		_exit(is_exited_with_zero(wait_status(pid)) ? 0 : 1);
+	}
+	TEST_RES(wait_status(pid), is_exited_with_zero(_ret));
+}
+END_TEST()
+
+FN_TEST(precedence)
+{
+	pid_t pid;
+
+	pid = TEST_SUCC(fork());
+	if (pid == 0) {
+		if (prctl(PR_SET_NO_NEW_PRIVS, 1, 0, 0, 0) < 0)
+			_exit(1);
+		if (install_filter(SYS_getppid, SECCOMP_RET_ERRNO | EPERM) < 0)
+			_exit(1);
+		if (install_filter(SYS_getppid, SECCOMP_RET_ALLOW) < 0)
+			_exit(1);
+		// The newer filter cannot relax the restriction.
+		if (syscall(SYS_getppid) != -1 || errno != EPERM)
+			_exit(1);
+
+		// The newer filter wins if the actions are the same.
+		if (install_filter(SYS_getppid, SECCOMP_RET_ERRNO | EACCES) < 0)
+			_exit(1);
+		if (syscall(SYS_getppid) != -1 || errno != EACCES)
+			_exit(1);
+		_exit(0);
+	}
+	TEST_RES(wait_status(pid), is_exited_with_zero(_ret));
+}
+END_TEST()
+
+static volatile int sigsys_code;
+static volatile int sigsys_errno;
+static volatile int sigsys_syscall;
+static volatile unsigned int sigsys_arch;
+
+static void handle_sigsys(int sig, siginfo_t *info, void *ucontext)
+{
+	sigsys_code = info->si_code;
+	sigsys_errno = info->si_errno;
+	sigsys_syscall = info->si_syscall;
+	sigsys_arch = info->si_arch;
+}
+
+FN_TEST(ret_trap)
+{
+	pid_t pid;
+
+	pid = TEST_SUCC(fork());
+	if (pid == 0) {
+		struct sigaction sa = {
+			.sa_sigaction = handle_sigsys,
+			.sa_flags = SA_SIGINFO,
+		};
+
+		if (sigaction(SIGSYS, &sa, NULL) < 0)
+			_exit(1);
+		if (prctl(PR_SET_NO_NEW_PRIVS, 1, 0, 0, 0) < 0)
+			_exit(1);
+		if (install_filter(SYS_getppid, SECCOMP_RET_TRAP | 42) < 0)
+			_exit(1);
+		syscall(SYS_getppid);
+		if (sigsys_code != SYS_SECCOMP || sigsys_errno != 42 ||
+		    sigsys_syscall != SYS_getppid || sigsys_arch != ARCH)
+			_exit(1);
+		_exit(0);
+	}
+	TEST_RES(wait_status(pid), is_exited_with_zero(_ret));
+
+	pid = TEST_SUCC(fork());
+	if (pid == 0) {
+		sigset_t mask;
+
+		// Blocked signals are unblocked, so the process is killed.
+		sigemptyset(&mask);
+		sigaddset(&mask, SIGSYS);
+		if (sigprocmask(SIG_BLOCK, &mask, NULL) < 0)
+			_exit(1);
+		if (prctl(PR_SET_NO_NEW_PRIVS, 1, 0, 0, 0) < 0)
+			_exit(1);
+		if (install_filter(SYS_getppid, SECCOMP_RET_TRAP) < 0)
+			_exit(1);
+		syscall(SYS_getppid);
+		_exit(0);
+	}
+	TEST_RES(wait_status(pid), is_killed_by(_ret, SIGSYS));
+}
+END_TEST()
+
+FN_TEST(ret_kill)
+{
+	pid_t pid;
+
+	pid = TEST_SUCC(fork());
+	if (pid == 0) {
+		if (prctl(PR_SET_NO_NEW_PRIVS, 1, 0, 0, 0) < 0)
+			_exit(1);
+		if (install_filter(SYS_getppid, SECCOMP_RET_KILL_PROCESS) < 0)
+			_exit(1);
+		syscall(SYS_getppid);
+		_exit(0);
+	}
+	TEST_RES(wait_status(pid), is_killed_by(_ret, SIGSYS));
+}
+END_TEST()
+
+FN_TEST(strict_mode)
+{
+	pid_t pid;
+
+	pid = TEST_SUCC(fork());
+	if (pid == 0) {
+		if (prctl(PR_SET_SECCOMP, SECCOMP_MODE_STRICT) < 0)
+			_exit(1);
+		if (write(STDOUT_FILENO, "", 0) != 0)
+			syscall(SYS_exit, 1);
+		syscall(SYS_exit, 0);
+	}
+	TEST_RES(wait_status(pid), is_exited_with_zero(_ret));
+
+	pid = TEST_SUCC(fork());
+	if (pid == 0) {
+		if (syscall(SYS_seccomp, SECCOMP_SET_MODE_STRICT, 0, NULL) < 0)
+			_exit(1);
+		syscall(SYS_getppid);
+		syscall(SYS_exit, 0);
+	}
+	TEST_RES(wait_status(pid), is_killed_by(_ret, SIGKILL));
+}
+END_TEST()
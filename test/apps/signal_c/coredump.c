@@ -0,0 +1,225 @@
+// SPDX-License-Identifier: MPL-2.0
+
+#define _GNU_SOURCE
+
+#include "../network/test.h"
+
+#include <elf.h>
+#include <fcntl.h>
+#include <signal.h>
+#include <sys/prctl.h>
+#include <sys/procfs.h>
+#include <sys/resource.h>
+#include <sys/stat.h>
+#include <sys/wait.h>
+#include <unistd.h>
+
+#define CORE_PATTERN_PATH "/proc/sys/kernel/core_pattern"
+#define TEST_DIR "/tmp/coredump"
+
+// The patterns contain `%`, so they cannot appear in the test expressions directly.
+static const char pid_pattern[] = "core.%p";
+static const char sig_pattern[] = "core.%p.%s\n";
+
+static char old_pattern[256];
+
+static int write_core_pattern(const char *pattern)
+{
+	int fd;
+	ssize_t len;
+
+	fd = open(CORE_PATTERN_PATH, O_WRONLY | O_TRUNC);
+	if (fd < 0)
+		return -1;
+	len = write(fd, pattern, strlen(pattern));
+	close(fd);
+
+	return len == strlen(pattern) ? 0 : -1;
+}
+
+static int read_core_pattern(char *buf, size_t size)
+{
+	int fd;
+	ssize_t len;
+
+	fd = open(CORE_PATTERN_PATH, O_RDONLY);
+	if (fd < 0)
+		return -1;
+	len = read(fd, buf, size - 1);
+	close(fd);
+	if (len < 0)
+		return -1;
+	buf[len] = 0;
+
+	return len;
+}
+
+static int set_core_limit(rlim_t limit)
+{
+	struct rlimit rlimit = { .rlim_cur = limit, .rlim_max = RLIM_INFINITY };
+
+	return setrlimit(RLIMIT_CORE, &rlimit);
+}
+
+static volatile long marker;
+
+// Forks a child that is killed by `SIGABRT`, and returns the PID and the wait status.
+static pid_t crash_child(int is_dumpable, int *status)
+{
+	pid_t pid;
+
+	pid = fork();
+	if (pid == 0) {
+		marker = 0x1234567890abcdef;
+		prctl(PR_SET_DUMPABLE, is_dumpable);
+		abort();
+	}
+	if (pid < 0 || waitpid(pid, status, 0) != pid)
+		return -1;
+
+	return pid;
+}
+
+FN_SETUP(cwd)
+{
+	CHECK(mkdir(TEST_DIR, 0700));
+	CHECK(chdir(TEST_DIR));
+
+	CHECK(read_core_pattern(old_pattern, sizeof(old_pattern)));
+	CHECK(write_core_pattern("core"));
+}
+END_SETUP()
+
+FN_TEST(core_pattern)
+{
+	char buf[256];
+	char long_pattern[200];
+
+	TEST_SUCC(write_core_pattern(sig_pattern));
+	TEST_RES(read_core_pattern(buf, sizeof(buf)),
+		 strcmp(buf, sig_pattern) == 0);
+
+	// The overlong pattern is truncated.
+	memset(long_pattern, 'a', sizeof(long_pattern) - 1);
+	long_pattern[sizeof(long_pattern) - 1] = 0;
+	TEST_SUCC(write_core_pattern(long_pattern));
+	TEST_RES(read_core_pattern(buf, sizeof(buf)), _ret == 128);
+
+	TEST_SUCC(write_core_pattern("core"));
+}
+END_TEST()
+
+FN_TEST(dumpable)
+{
+	TEST_RES(prctl(PR_GET_DUMPABLE), _ret == 1);
+	TEST_SUCC(prctl(PR_SET_DUMPABLE, 0));
+	TEST_RES(prctl(PR_GET_DUMPABLE), _ret == 0);
+	TEST_ERRNO(prctl(PR_SET_DUMPABLE, 2), EINVAL);
+	TEST_SUCC(prctl(PR_SET_DUMPABLE, 1));
+	TEST_RES(prctl(PR_GET_DUMPABLE), _ret == 1);
+}
+END_TEST()
+
+FN_TEST(no_dump)
+{
+	int status;
+
+	// The core file size limit is zero.
+	TEST_SUCC(set_core_limit(0));
+	TEST_RES(crash_child(1, &status),
+		 _ret > 0 && WIFSIGNALED(status) &&
+			 WTERMSIG(status) == SIGABRT && !WCOREDUMP(status));
+	TEST_ERRNO(access("core", F_OK), ENOENT);
+
+	// The process is not dumpable.
+	TEST_SUCC(set_core_limit(RLIM_INFINITY));
+	TEST_RES(crash_child(0, &status),
+		 _ret > 0 && WIFSIGNALED(status) &&
+			 WTERMSIG(status) == SIGABRT && !WCOREDUMP(status));
+	TEST_ERRNO(access("core", F_OK), ENOENT);
+
+	TEST_SUCC(set_core_limit(0));
+}
+END_TEST()
+
+// Checks the core file of the child, and returns the value of `marker` in the core file.
+static long check_core_file(const char *path, pid_t pid)
+{
+	static char buf[64 * 1024];
+	Elf64_Ehdr *ehdr = (Elf64_Ehdr *)buf;
+	Elf64_Phdr *phdr;
+	Elf64_Nhdr *nhdr;
+	struct elf_prstatus *prstatus = NULL;
+	ssize_t len;
+	long value = -1;
+	int fd, i;
+
+	fd = open(path, O_RDONLY);
+	if (fd < 0)
+		return -1;
+	len = read(fd, buf, sizeof(buf));
+
+	if (len < sizeof(*ehdr) || memcmp(ehdr->e_ident, ELFMAG, SELFMAG) != 0 ||
+	    ehdr->e_type != ET_CORE ||
+	    ehdr->e_phoff + ehdr->e_phnum * sizeof(*phdr) > len)
+		goto out;
+
+	for (i = 0; i < ehdr->e_phnum; i++) {
+		phdr = (Elf64_Phdr *)(buf + ehdr->e_phoff) + i;
+
+		if (phdr->p_type == PT_NOTE &&
+		    phdr->p_offset + sizeof(*nhdr) + 8 + sizeof(*prstatus) <=
+			    len) {
+			// The first note describes the status of the thread.
+			nhdr = (Elf64_Nhdr *)(buf + phdr->p_offset);
+			if (nhdr->n_type == NT_PRSTATUS &&
+			    nhdr->n_descsz == sizeof(*prstatus))
+				prstatus = (void *)((char *)(nhdr + 1) + 8);
+		}
+
+		if (phdr->p_type == PT_LOAD &&
+		    (unsigned long)&marker >= phdr->p_vaddr &&
+		    (unsigned long)&marker < phdr->p_vaddr + phdr->p_filesz)
+			pread(fd, &value, sizeof(value),
+			      phdr->p_offset + (unsigned long)&marker -
+				      phdr->p_vaddr);
+	}
+
+	if (prstatus == NULL || prstatus->pr_pid != pid ||
+	    prstatus->pr_cursig != SIGABRT)
+		value = -1;
+
+out:
+	close(fd);
+	return value;
+}
+
+FN_TEST(dump)
+{
+	char path[64];
+	int status;
+	pid_t pid;
+
+	TEST_SUCC(set_core_limit(RLIM_INFINITY));
+	TEST_SUCC(write_core_pattern(pid_pattern));
+
+	pid = TEST_RES(crash_child(1, &status),
+		       _ret > 0 && WIFSIGNALED(status) &&
+			       WTERMSIG(status) == SIGABRT &&
+			       WCOREDUMP(status));
+	snprintf(path, sizeof(path), "core.%d", pid);
+	TEST_RES(check_core_file(path, pid), _ret == 0x1234567890abcdef);
+	TEST_SUCC(unlink(path));
+
+	TEST_SUCC(write_core_pattern("core"));
+	TEST_SUCC(set_core_limit(0));
+}
+END_TEST()
+
+FN_SETUP(cleanup)
+{
+	CHECK(write_core_pattern(old_pattern));
+	CHECK(chdir("/"));
+	CHECK(rmdir(TEST_DIR));
+}
+END_SETUP()
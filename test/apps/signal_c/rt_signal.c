@@ -0,0 +1,268 @@
+// SPDX-License-Identifier: MPL-2.0
+
+#define _GNU_SOURCE
+
+#include "../network/test.h"
+
+#include <setjmp.h>
+#include <signal.h>
+#include <stdint.h>
+#include <sys/syscall.h>
+#include <sys/wait.h>
+#include <unistd.h>
+
+#ifndef SS_AUTODISARM
+#define SS_AUTODISARM (1U << 31)
+#endif
+
+#define MAX_INFOS 8
+
+static volatile int num_infos;
+static siginfo_t infos[MAX_INFOS];
+
+static void record_info(int sig, siginfo_t *info, void *ucontext)
+{
+	(void)sig;
+	(void)ucontext;
+
+	if (num_infos < MAX_INFOS)
+		infos[num_infos++] = *info;
+}
+
+static void set_handler(int sig, void (*handler)(int, siginfo_t *, void *),
+			int flags)
+{
+	struct sigaction sa = { .sa_sigaction = handler,
+				.sa_flags = SA_SIGINFO | flags };
+
+	sigemptyset(&sa.sa_mask);
+	CHECK(sigaction(sig, &sa, NULL));
+}
+
+static int block_signal(int how, int sig)
+{
+	sigset_t set;
+
+	sigemptyset(&set);
+	sigaddset(&set, sig);
+	return sigprocmask(how, &set, NULL);
+}
+
+FN_TEST(sigqueue)
+{
+	struct sigaction sa = { .sa_sigaction = record_info,
+				.sa_flags = SA_SIGINFO };
+	union sigval value;
+	sigset_t pending;
+	int i;
+
+	// All signals are blocked while the handler is running, so the handlers run one by one.
+	sigfillset(&sa.sa_mask);
+	TEST_SUCC(sigaction(SIGRTMIN, &sa, NULL));
+	TEST_SUCC(sigaction(SIGRTMIN + 1, &sa, NULL));
+	num_infos = 0;
+
+	// Real-time signals are queued with their values while they are blocked.
+	TEST_SUCC(block_signal(SIG_BLOCK, SIGRTMIN));
+	TEST_SUCC(block_signal(SIG_BLOCK, SIGRTMIN + 1));
+	for (i = 0; i < 3; i++) {
+		value.sival_int = 100 + i;
+		TEST_SUCC(sigqueue(getpid(), SIGRTMIN + 1 - (i & 1), value));
+	}
+	TEST_RES(sigpending(&pending),
+		 sigismember(&pending, SIGRTMIN) &&
+			 sigismember(&pending, SIGRTMIN + 1));
+	TEST_RES(num_infos, _ret == 0);
+
+	// Lower-numbered signals are delivered first, and the signals of the same number are
+	// delivered in the order they were sent.
+	TEST_SUCC(sigprocmask(SIG_UNBLOCK, &pending, NULL));
+	TEST_RES(num_infos, _ret == 3);
+	TEST_RES(infos[0].si_signo, _ret == SIGRTMIN);
+	TEST_RES(infos[0].si_value.sival_int, _ret == 101);
+	TEST_RES(infos[1].si_signo, _ret == SIGRTMIN + 1);
+	TEST_RES(infos[1].si_value.sival_int, _ret == 100);
+	TEST_RES(infos[2].si_signo, _ret == SIGRTMIN + 1);
+	TEST_RES(infos[2].si_value.sival_int, _ret == 102);
+	TEST_RES(infos[2].si_code, _ret == SI_QUEUE);
+	TEST_RES(infos[2].si_pid, _ret == getpid());
+	TEST_RES(infos[2].si_uid, _ret == getuid());
+
+	signal(SIGRTMIN, SIG_DFL);
+	signal(SIGRTMIN + 1, SIG_DFL);
+}
+END_TEST()
+
+FN_TEST(rt_sigqueueinfo)
+{
+	siginfo_t info = { .si_code = SI_USER };
+	int status;
+	pid_t pid;
+
+	set_handler(SIGRTMIN, record_info, 0);
+	num_infos = 0;
+
+	// The information is delivered as is, except the signal number.
+	info.si_signo = SIGUSR1;
+	info.si_pid = 1234;
+	TEST_SUCC(syscall(SYS_rt_sigqueueinfo, getpid(), SIGRTMIN, &info));
+	TEST_RES(num_infos, _ret == 1);
+	TEST_RES(infos[0].si_signo, _ret == SIGRTMIN);
+	TEST_RES(infos[0].si_code, _ret == SI_USER);
+	TEST_RES(infos[0].si_pid, _ret == 1234);
+
+	TEST_SUCC(syscall(SYS_rt_tgsigqueueinfo, getpid(), gettid(), SIGRTMIN,
+			  &info));
+	TEST_RES(num_infos, _ret == 2);
+	TEST_ERRNO(syscall(SYS_rt_tgsigqueueinfo, getpid(), 0, SIGRTMIN, &info),
+		   EINVAL);
+
+	// Other processes cannot receive signals that pretend to be sent by the kernel or `kill`.
+	pid = TEST_SUCC(fork());
+	if (pid == 0) {
+		pause();
+		_exit(EXIT_FAILURE);
+	}
+	TEST_ERRNO(syscall(SYS_rt_sigqueueinfo, pid, SIGKILL, &info), EPERM);
+	info.si_code = SI_TKILL;
+	TEST_ERRNO(syscall(SYS_rt_sigqueueinfo, pid, SIGKILL, &info), EPERM);
+	info.si_code = SI_QUEUE;
+	TEST_SUCC(syscall(SYS_rt_sigqueueinfo, pid, SIGKILL, &info));
+	TEST_RES(waitpid(pid, &status, 0),
+		 _ret == pid && WIFSIGNALED(status) &&
+			 WTERMSIG(status) == SIGKILL);
+
+	signal(SIGRTMIN, SIG_DFL);
+}
+END_TEST()
+
+static volatile int handler_runs;
+static volatile int is_self_blocked;
+
+static void check_self_blocked(int sig, siginfo_t *info, void *ucontext)
+{
+	sigset_t set;
+
+	(void)info;
+	(void)ucontext;
+
+	sigprocmask(SIG_BLOCK, NULL, &set);
+	is_self_blocked = sigismember(&set, sig);
+	handler_runs++;
+}
+
+FN_TEST(nodefer_resethand)
+{
+	struct sigaction sa;
+	sigset_t set;
+
+	// Without `SA_NODEFER`, the signal is blocked while its handler is running.
+	set_handler(SIGUSR1, check_self_blocked, 0);
+	handler_runs = 0;
+	TEST_SUCC(raise(SIGUSR1));
+	TEST_RES(handler_runs, _ret == 1);
+	TEST_RES(is_self_blocked, _ret == 1);
+
+	set_handler(SIGUSR1, check_self_blocked, SA_NODEFER);
+	TEST_SUCC(raise(SIGUSR1));
+	TEST_RES(handler_runs, _ret == 2);
+	TEST_RES(is_self_blocked, _ret == 0);
+
+	// With `SA_RESETHAND`, the handler is reset to the default after it runs once.
+	set_handler(SIGUSR1, check_self_blocked, SA_RESETHAND);
+	TEST_SUCC(raise(SIGUSR1));
+	TEST_RES(handler_runs, _ret == 3);
+	TEST_RES(sigaction(SIGUSR1, NULL, &sa), sa.sa_handler == SIG_DFL);
+
+	// The signal mask before the handler runs is restored, even if some signals in the mask
+	// are also in the mask of the handler.
+	sa.sa_sigaction = check_self_blocked;
+	sa.sa_flags = SA_SIGINFO;
+	sigemptyset(&sa.sa_mask);
+	sigaddset(&sa.sa_mask, SIGUSR2);
+	TEST_SUCC(sigaction(SIGUSR1, &sa, NULL));
+	TEST_SUCC(block_signal(SIG_BLOCK, SIGUSR2));
+	TEST_SUCC(raise(SIGUSR1));
+	TEST_RES(handler_runs, _ret == 4);
+	TEST_RES(sigprocmask(SIG_BLOCK, NULL, &set),
+		 sigismember(&set, SIGUSR2) && !sigismember(&set, SIGUSR1));
+	TEST_SUCC(block_signal(SIG_UNBLOCK, SIGUSR2));
+
+	signal(SIGUSR1, SIG_DFL);
+}
+END_TEST()
+
+#define ALT_STACK_SIZE (4 * 4096)
+
+static char alt_stack[ALT_STACK_SIZE];
+static volatile int is_on_alt_stack;
+static stack_t handler_ss;
+static sigjmp_buf jump_buf;
+
+static void check_alt_stack(int sig, siginfo_t *info, void *ucontext)
+{
+	char local;
+
+	(void)sig;
+	(void)info;
+	(void)ucontext;
+
+	is_on_alt_stack = &local >= alt_stack &&
+			  &local < alt_stack + ALT_STACK_SIZE;
+	sigaltstack(NULL, &handler_ss);
+	handler_runs++;
+}
+
+static void jump_out(int sig, siginfo_t *info, void *ucontext)
+{
+	check_alt_stack(sig, info, ucontext);
+	siglongjmp(jump_buf, 1);
+}
+
+FN_TEST(sigaltstack)
+{
+	stack_t ss = { .ss_sp = alt_stack, .ss_size = ALT_STACK_SIZE };
+	stack_t old_ss;
+
+	TEST_SUCC(sigaltstack(&ss, NULL));
+	handler_runs = 0;
+
+	// The alternate signal stack is used only with `SA_ONSTACK`.
+	set_handler(SIGUSR1, check_alt_stack, 0);
+	TEST_SUCC(raise(SIGUSR1));
+	TEST_RES(is_on_alt_stack, _ret == 0);
+	TEST_RES(handler_ss.ss_flags, _ret == 0);
+
+	set_handler(SIGUSR1, check_alt_stack, SA_ONSTACK);
+	TEST_SUCC(raise(SIGUSR1));
+	TEST_RES(is_on_alt_stack, _ret == 1);
+	TEST_RES(handler_ss.ss_flags, _ret == SS_ONSTACK);
+
+	// The stack can be used again after the handler jumps out of itself.
+	set_handler(SIGUSR1, jump_out, SA_ONSTACK);
+	if (sigsetjmp(jump_buf, 1) == 0)
+		raise(SIGUSR1);
+	TEST_RES(handler_runs, _ret == 3);
+	TEST_RES(is_on_alt_stack, _ret == 1);
+	TEST_RES(sigaltstack(NULL, &old_ss), old_ss.ss_flags == 0);
+	TEST_SUCC(sigaltstack(&ss, NULL));
+
+	// With `SS_AUTODISARM`, the stack is disabled while the handler is running.
+	ss.ss_flags = SS_AUTODISARM;
+	TEST_SUCC(sigaltstack(&ss, NULL));
+	set_handler(SIGUSR1, check_alt_stack, SA_ONSTACK);
+	TEST_SUCC(raise(SIGUSR1));
+	TEST_RES(is_on_alt_stack, _ret == 1);
+	TEST_RES(handler_ss.ss_flags, _ret == SS_DISABLE);
+	TEST_RES(sigaltstack(NULL, &old_ss),
+		 old_ss.ss_flags == SS_AUTODISARM && old_ss.ss_sp == alt_stack);
+
+	ss.ss_flags = SS_ONSTACK | SS_DISABLE;
+	TEST_ERRNO(sigaltstack(&ss, NULL), EINVAL);
+	ss.ss_flags = SS_DISABLE;
+	TEST_SUCC(sigaltstack(&ss, NULL));
+	TEST_RES(sigaltstack(NULL, &old_ss), old_ss.ss_flags == SS_DISABLE);
+
+	signal(SIGUSR1, SIG_DFL);
+}
+END_TEST()
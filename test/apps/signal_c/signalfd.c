@@ -0,0 +1,113 @@
+// SPDX-License-Identifier: MPL-2.0
+
+#define _GNU_SOURCE
+
+#include <fcntl.h>
+#include <poll.h>
+#include <pthread.h>
+#include <signal.h>
+#include <sys/signalfd.h>
+#include <sys/syscall.h>
+#include <unistd.h>
+
+#include "../network/test.h"
+
+static int sfd;
+static sigset_t mask;
+
+FN_SETUP(create)
+{
+	sigemptyset(&mask);
+	sigaddset(&mask, SIGUSR1);
+	sigaddset(&mask, SIGUSR2);
+	CHECK(sigprocmask(SIG_BLOCK, &mask, NULL));
+
+	sfd = CHECK(signalfd(-1, &mask, SFD_NONBLOCK | SFD_CLOEXEC));
+}
+END_SETUP()
+
+FN_TEST(create_flags)
+{
+	TEST_RES(fcntl(sfd, F_GETFD), _ret == FD_CLOEXEC);
+	TEST_RES(fcntl(sfd, F_GETFL), (_ret & O_NONBLOCK) != 0);
+
+	TEST_ERRNO(signalfd(-1, &mask, 0x1), EINVAL);
+	TEST_ERRNO(syscall(SYS_signalfd4, -1, &mask, 4, 0), EINVAL);
+	TEST_ERRNO(signalfd(STDIN_FILENO, &mask, 0), EINVAL);
+}
+END_TEST()
+
+FN_TEST(read_signals)
+{
+	struct signalfd_siginfo ssi[2];
+	struct pollfd pfd = { .fd = sfd, .events = POLLIN };
+
+	TEST_ERRNO(read(sfd, ssi, sizeof(ssi[0])), EAGAIN);
+	TEST_RES(poll(&pfd, 1, 0), _ret == 0);
+
+	TEST_SUCC(kill(getpid(), SIGUSR1));
+	TEST_RES(poll(&pfd, 1, 0), _ret == 1 && pfd.revents == POLLIN);
+
+	TEST_ERRNO(read(sfd, ssi, sizeof(ssi[0]) - 1), EINVAL);
+	TEST_RES(read(sfd, ssi, sizeof(ssi)),
+		 _ret == sizeof(ssi[0]) && ssi[0].ssi_signo == SIGUSR1 &&
+			 ssi[0].ssi_code == SI_USER &&
+			 ssi[0].ssi_pid == getpid() &&
+			 ssi[0].ssi_uid == getuid());
+	TEST_ERRNO(read(sfd, ssi, sizeof(ssi[0])), EAGAIN);
+
+	TEST_SUCC(kill(getpid(), SIGUSR1));
+	TEST_SUCC(kill(getpid(), SIGUSR2));
+	TEST_RES(read(sfd, ssi, sizeof(ssi)), _ret == sizeof(ssi));
+	TEST_ERRNO(read(sfd, ssi, sizeof(ssi[0])), EAGAIN);
+}
+END_TEST()
+
+FN_TEST(update_mask)
+{
+	struct signalfd_siginfo ssi;
+	sigset_t new_mask;
+
+	sigemptyset(&new_mask);
+	sigaddset(&new_mask, SIGUSR2);
+	TEST_RES(signalfd(sfd, &new_mask, 0), _ret == sfd);
+
+	TEST_SUCC(kill(getpid(), SIGUSR1));
+	TEST_ERRNO(read(sfd, &ssi, sizeof(ssi)), EAGAIN);
+
+	TEST_RES(signalfd(sfd, &mask, 0), _ret == sfd);
+	TEST_RES(read(sfd, &ssi, sizeof(ssi)), ssi.ssi_signo == SIGUSR1);
+}
+END_TEST()
+
+static void *read_in_thread(void *arg)
+{
+	struct signalfd_siginfo ssi;
+
+	if (read(sfd, &ssi, sizeof(ssi)) != sizeof(ssi))
+		return NULL;
+	return (void *)(long)ssi.ssi_signo;
+}
+
+FN_TEST(process_directed)
+{
+	struct pollfd pfd = { .fd = sfd, .events = POLLIN };
+	pthread_t thread;
+	void *signo;
+
+	// The signal is directed at the process, so another thread can read it.
+	TEST_SUCC(kill(getpid(), SIGUSR2));
+	TEST_RES(poll(&pfd, 1, 0), _ret == 1 && pfd.revents == POLLIN);
+	TEST_SUCC(pthread_create(&thread, NULL, read_in_thread, NULL));
+	TEST_RES(pthread_join(thread, &signo),
+		 _ret == 0 && signo == (void *)SIGUSR2);
+	TEST_RES(poll(&pfd, 1, 0), _ret == 0);
+}
+END_TEST()
+
+FN_SETUP(cleanup)
+{
+	CHECK(close(sfd));
+	CHECK(sigprocmask(SIG_UNBLOCK, &mask, NULL));
+}
+END_SETUP()
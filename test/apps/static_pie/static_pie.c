@@ -0,0 +1,85 @@
+// SPDX-License-Identifier: MPL-2.0
+
+#define _GNU_SOURCE
+
+#include "../network/test.h"
+
+#include <elf.h>
+#include <limits.h>
+#include <link.h>
+#include <stdio.h>
+#include <string.h>
+#include <sys/auxv.h>
+#include <unistd.h>
+
+extern const ElfW(Ehdr) __ehdr_start;
+extern void _start(void);
+
+static int is_stack_executable(void)
+{
+	FILE *maps;
+	char line[256];
+	char perms[5];
+	int ret = -1;
+
+	maps = fopen("/proc/self/maps", "r");
+	if (maps == NULL)
+		return -1;
+	while (fgets(line, sizeof(line), maps) != NULL) {
+		if (strstr(line, "[stack]") == NULL)
+			continue;
+		if (sscanf(line, "%*x-%*x %4s", perms) == 1)
+			ret = perms[2] == 'x';
+		break;
+	}
+	fclose(maps);
+
+	errno = 0;
+	return ret;
+}
+
+FN_TEST(load_address)
+{
+	long base = (long)&__ehdr_start;
+
+	// A static PIE is loaded without an interpreter.
+	TEST_RES(getauxval(AT_BASE), _ret == 0);
+	TEST_RES(getauxval(AT_ENTRY), _ret == (long)&_start);
+	TEST_RES(getauxval(AT_PHDR), _ret == base + (long)__ehdr_start.e_phoff);
+	TEST_RES(getauxval(AT_PHNUM), _ret == __ehdr_start.e_phnum);
+	TEST_RES(getauxval(AT_PHENT), _ret == (long)sizeof(ElfW(Phdr)));
+}
+END_TEST()
+
+FN_TEST(ids)
+{
+	TEST_RES(getauxval(AT_UID), _ret == getuid());
+	TEST_RES(getauxval(AT_EUID), _ret == geteuid());
+	TEST_RES(getauxval(AT_GID), _ret == getgid());
+	TEST_RES(getauxval(AT_EGID), _ret == getegid());
+	TEST_RES(getauxval(AT_SECURE), _ret == 0);
+}
+END_TEST()
+
+FN_TEST(misc)
+{
+	char path[PATH_MAX];
+	ssize_t len;
+
+	TEST_RES(getauxval(AT_PAGESZ), _ret == getpagesize());
+	TEST_RES(getauxval(AT_CLKTCK), _ret == 100);
+	TEST_RES(getauxval(AT_RANDOM), _ret != 0);
+	TEST_RES(getauxval(AT_PLATFORM),
+		 strcmp((const char *)_ret, "x86_64") == 0);
+
+	len = TEST_SUCC(readlink("/proc/self/exe", path, sizeof(path) - 1));
+	path[len] = '\0';
+	TEST_RES(getauxval(AT_EXECFN), strcmp((const char *)_ret, path) == 0);
+}
+END_TEST()
+
+FN_TEST(non_executable_stack)
+{
+	TEST_RES(is_stack_executable(), _ret == 0);
+}
+END_TEST()
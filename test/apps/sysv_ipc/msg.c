@@ -0,0 +1,170 @@
+// SPDX-License-Identifier: MPL-2.0
+
+#define _GNU_SOURCE
+
+#include <string.h>
+#include <sys/ipc.h>
+#include <sys/msg.h>
+#include <sys/wait.h>
+#include <unistd.h>
+
+#include "../network/test.h"
+
+#define MSG_KEY 0x4d534701
+#define MSG_MAX 8192
+
+struct message {
+	long mtype;
+	char mtext[MSG_MAX];
+};
+
+static struct message msg;
+
+static int wait_exit_code(pid_t pid)
+{
+	int status;
+
+	if (waitpid(pid, &status, 0) != pid)
+		return -1;
+	if (!WIFEXITED(status))
+		return -1;
+
+	return WEXITSTATUS(status);
+}
+
+static int send_message(int msqid, long mtype, const char *text)
+{
+	msg.mtype = mtype;
+	strcpy(msg.mtext, text);
+	return msgsnd(msqid, &msg, strlen(text), IPC_NOWAIT);
+}
+
+static long receive_type(int msqid, long msgtyp, int msgflg)
+{
+	memset(&msg, 0, sizeof(msg));
+	if (msgrcv(msqid, &msg, MSG_MAX, msgtyp, msgflg | IPC_NOWAIT) < 0)
+		return -1;
+	return msg.mtype;
+}
+
+FN_TEST(send_and_receive)
+{
+	struct msqid_ds ds;
+	int msqid;
+
+	msqid = TEST_SUCC(msgget(IPC_PRIVATE, IPC_CREAT | 0600));
+	TEST_SUCC(send_message(msqid, 1, "hello"));
+	TEST_SUCC(send_message(msqid, 2, "world!"));
+
+	TEST_RES(msgctl(msqid, IPC_STAT, &ds),
+		 ds.msg_qnum == 2 && ds.msg_cbytes == 11 &&
+			 ds.msg_lspid == getpid() && ds.msg_lrpid == 0);
+
+	memset(&msg, 0, sizeof(msg));
+	TEST_RES(msgrcv(msqid, &msg, MSG_MAX, 0, 0),
+		 _ret == 5 && msg.mtype == 1 && strcmp(msg.mtext, "hello") == 0);
+	TEST_RES(msgctl(msqid, IPC_STAT, &ds),
+		 ds.msg_qnum == 1 && ds.msg_lrpid == getpid());
+
+	TEST_SUCC(msgctl(msqid, IPC_RMID, NULL));
+	TEST_ERRNO(msgctl(msqid, IPC_STAT, &ds), EINVAL);
+	TEST_ERRNO(send_message(msqid, 1, "hello"), EINVAL);
+}
+END_TEST()
+
+FN_TEST(key_lookup)
+{
+	int msqid;
+
+	msqid = TEST_SUCC(msgget(MSG_KEY, IPC_CREAT | IPC_EXCL | 0600));
+	TEST_ERRNO(msgget(MSG_KEY, IPC_CREAT | IPC_EXCL | 0600), EEXIST);
+	TEST_RES(msgget(MSG_KEY, 0), _ret == msqid);
+
+	TEST_SUCC(msgctl(msqid, IPC_RMID, NULL));
+	TEST_ERRNO(msgget(MSG_KEY, 0), ENOENT);
+}
+END_TEST()
+
+FN_TEST(message_types)
+{
+	int msqid;
+
+	msqid = TEST_SUCC(msgget(IPC_PRIVATE, IPC_CREAT | 0600));
+	TEST_SUCC(send_message(msqid, 3, "a"));
+	TEST_SUCC(send_message(msqid, 1, "b"));
+	TEST_SUCC(send_message(msqid, 2, "c"));
+	TEST_SUCC(send_message(msqid, 1, "d"));
+
+	TEST_RES(receive_type(msqid, 2, 0),
+		 _ret == 2 && strcmp(msg.mtext, "c") == 0);
+	// The first message with the lowest type is received.
+	TEST_RES(receive_type(msqid, -2, 0),
+		 _ret == 1 && strcmp(msg.mtext, "b") == 0);
+	TEST_RES(receive_type(msqid, 1, MSG_EXCEPT),
+		 _ret == 3 && strcmp(msg.mtext, "a") == 0);
+	TEST_ERRNO(receive_type(msqid, 2, 0), ENOMSG);
+	TEST_RES(receive_type(msqid, 0, 0),
+		 _ret == 1 && strcmp(msg.mtext, "d") == 0);
+	TEST_ERRNO(receive_type(msqid, 0, 0), ENOMSG);
+
+	TEST_SUCC(msgctl(msqid, IPC_RMID, NULL));
+}
+END_TEST()
+
+FN_TEST(message_size)
+{
+	int msqid;
+
+	msqid = TEST_SUCC(msgget(IPC_PRIVATE, IPC_CREAT | 0600));
+	TEST_SUCC(send_message(msqid, 1, "0123456789"));
+
+	TEST_ERRNO(msgrcv(msqid, &msg, 4, 0, IPC_NOWAIT), E2BIG);
+	memset(&msg, 0, sizeof(msg));
+	TEST_RES(msgrcv(msqid, &msg, 4, 0, IPC_NOWAIT | MSG_NOERROR),
+		 _ret == 4 && strcmp(msg.mtext, "0123") == 0);
+
+	msg.mtype = 0;
+	TEST_ERRNO(msgsnd(msqid, &msg, 1, IPC_NOWAIT), EINVAL);
+	msg.mtype = 1;
+	TEST_ERRNO(msgsnd(msqid, &msg, MSG_MAX + 1, IPC_NOWAIT), EINVAL);
+
+	// The queue is full after two messages of the maximum size.
+	TEST_SUCC(msgsnd(msqid, &msg, MSG_MAX, IPC_NOWAIT));
+	TEST_SUCC(msgsnd(msqid, &msg, MSG_MAX, IPC_NOWAIT));
+	TEST_ERRNO(msgsnd(msqid, &msg, 1, IPC_NOWAIT), EAGAIN);
+
+	TEST_SUCC(msgctl(msqid, IPC_RMID, NULL));
+}
+END_TEST()
+
+FN_TEST(blocking_receive)
+{
+	int msqid;
+	pid_t pid;
+
+	msqid = TEST_SUCC(msgget(IPC_PRIVATE, IPC_CREAT | 0600));
+
+	pid = TEST_SUCC(fork());
+	if (pid == 0) {
+		usleep(100000);
+		msg.mtype = 5;
+		strcpy(msg.mtext, "wake");
+		_exit(msgsnd(msqid, &msg, 4, 0) < 0);
+	}
+	memset(&msg, 0, sizeof(msg));
+	TEST_RES(msgrcv(msqid, &msg, MSG_MAX, 5, 0),
+		 _ret == 4 && strcmp(msg.mtext, "wake") == 0);
+	TEST_RES(wait_exit_code(pid), _ret == 0);
+
+	// The waiting receiver fails after the queue is removed.
+	pid = TEST_SUCC(fork());
+	if (pid == 0) {
+		if (msgrcv(msqid, &msg, MSG_MAX, 0, 0) >= 0 || errno != EIDRM)
+			_exit(1);
+		_exit(0);
+	}
+	usleep(100000);
+	TEST_SUCC(msgctl(msqid, IPC_RMID, NULL));
+	TEST_RES(wait_exit_code(pid), _ret == 0);
+}
+END_TEST()
@@ -0,0 +1,122 @@
+// SPDX-License-Identifier: MPL-2.0
+
+#define _GNU_SOURCE
+
+#include <sys/ipc.h>
+#include <sys/sem.h>
+#include <sys/wait.h>
+#include <unistd.h>
+
+#include "../network/test.h"
+
+static int wait_exit_code(pid_t pid)
+{
+	int status;
+
+	if (waitpid(pid, &status, 0) != pid)
+		return -1;
+	if (!WIFEXITED(status))
+		return -1;
+
+	return WEXITSTATUS(status);
+}
+
+static int sem_add(int semid, short op, short flags)
+{
+	struct sembuf sop = { .sem_num = 0, .sem_op = op, .sem_flg = flags };
+
+	return semop(semid, &sop, 1);
+}
+
+FN_TEST(undo_on_exit)
+{
+	int semid;
+	pid_t pid;
+
+	semid = TEST_SUCC(semget(IPC_PRIVATE, 1, IPC_CREAT | 0600));
+	TEST_SUCC(semctl(semid, 0, SETVAL, 1));
+
+	pid = TEST_SUCC(fork());
+	if (pid == 0) {
+		// The operations without `SEM_UNDO` are not undone.
+		if (sem_add(semid, -1, SEM_UNDO) < 0 || sem_add(semid, 3, 0) < 0 ||
+		    sem_add(semid, 2, SEM_UNDO) < 0)
+			_exit(1);
+		_exit(semctl(semid, 0, GETVAL) != 5);
+	}
+	TEST_RES(wait_exit_code(pid), _ret == 0);
+	TEST_RES(semctl(semid, 0, GETVAL), _ret == 4);
+	TEST_RES(semctl(semid, 0, GETPID), _ret == pid);
+
+	TEST_SUCC(semctl(semid, 0, IPC_RMID));
+}
+END_TEST()
+
+FN_TEST(undo_wakes_waiters)
+{
+	int semid;
+	pid_t pid;
+
+	semid = TEST_SUCC(semget(IPC_PRIVATE, 1, IPC_CREAT | 0600));
+	TEST_SUCC(semctl(semid, 0, SETVAL, 1));
+
+	// The child holds the semaphore and exits without releasing it.
+	pid = TEST_SUCC(fork());
+	if (pid == 0) {
+		if (sem_add(semid, -1, SEM_UNDO) < 0)
+			_exit(1);
+		usleep(100000);
+		_exit(0);
+	}
+	usleep(50000);
+	TEST_RES(semctl(semid, 0, GETVAL), _ret == 0);
+	TEST_SUCC(sem_add(semid, -1, 0));
+	TEST_RES(wait_exit_code(pid), _ret == 0);
+	TEST_RES(semctl(semid, 0, GETVAL), _ret == 0);
+
+	TEST_SUCC(semctl(semid, 0, IPC_RMID));
+}
+END_TEST()
+
+FN_TEST(setval_clears_undo)
+{
+	int semid;
+	pid_t pid;
+
+	semid = TEST_SUCC(semget(IPC_PRIVATE, 1, IPC_CREAT | 0600));
+
+	pid = TEST_SUCC(fork());
+	if (pid == 0) {
+		if (sem_add(semid, 2, SEM_UNDO) < 0)
+			_exit(1);
+		if (semctl(semid, 0, SETVAL, 1) < 0)
+			_exit(1);
+		_exit(0);
+	}
+	TEST_RES(wait_exit_code(pid), _ret == 0);
+	TEST_RES(semctl(semid, 0, GETVAL), _ret == 1);
+
+	TEST_SUCC(semctl(semid, 0, IPC_RMID));
+}
+END_TEST()
+
+FN_TEST(undo_is_clamped)
+{
+	int semid;
+	pid_t pid;
+
+	semid = TEST_SUCC(semget(IPC_PRIVATE, 1, IPC_CREAT | 0600));
+
+	// The value cannot become negative when the operations are undone.
+	pid = TEST_SUCC(fork());
+	if (pid == 0) {
+		if (sem_add(semid, 2, SEM_UNDO) < 0 || sem_add(semid, -2, 0) < 0)
+			_exit(1);
+		_exit(0);
+	}
+	TEST_RES(wait_exit_code(pid), _ret == 0);
+	TEST_RES(semctl(semid, 0, GETVAL), _ret == 0);
+
+	TEST_SUCC(semctl(semid, 0, IPC_RMID));
+}
+END_TEST()
@@ -0,0 +1,161 @@
+// SPDX-License-Identifier: MPL-2.0
+
+#define _GNU_SOURCE
+
+#include <fcntl.h>
+#include <string.h>
+#include <sys/ipc.h>
+#include <sys/mman.h>
+#include <sys/shm.h>
+#include <sys/wait.h>
+#include <unistd.h>
+
+#include "../network/test.h"
+
+#define SHM_KEY 0x53484d01
+#define SHM_SIZE 8192
+
+static int wait_exit_code(pid_t pid)
+{
+	int status;
+
+	if (waitpid(pid, &status, 0) != pid)
+		return -1;
+	if (!WIFEXITED(status))
+		return -1;
+
+	return WEXITSTATUS(status);
+}
+
+static int num_attaches(int shmid)
+{
+	struct shmid_ds ds;
+
+	if (shmctl(shmid, IPC_STAT, &ds) < 0)
+		return -1;
+	return ds.shm_nattch;
+}
+
+FN_TEST(attach_and_fork)
+{
+	struct shmid_ds ds;
+	char *addr;
+	int shmid;
+	pid_t pid;
+
+	shmid = TEST_SUCC(shmget(IPC_PRIVATE, SHM_SIZE, IPC_CREAT | 0600));
+	TEST_RES(shmctl(shmid, IPC_STAT, &ds),
+		 ds.shm_segsz == SHM_SIZE && ds.shm_nattch == 0 &&
+			 ds.shm_cpid == getpid() && ds.shm_perm.mode == 0600);
+
+	addr = (char *)TEST_SUCC((long)shmat(shmid, NULL, 0));
+	strcpy(addr, "parent");
+	TEST_RES(num_attaches(shmid), _ret == 1);
+
+	pid = TEST_SUCC(fork());
+	if (pid == 0) {
+		char *child_addr;
+
+		// The attach is inherited by the child.
+		if (num_attaches(shmid) != 2)
+			_exit(1);
+		child_addr = shmat(shmid, NULL, 0);
+		if (child_addr == (void *)-1 || strcmp(child_addr, "parent"))
+			_exit(1);
+		strcpy(child_addr, "child");
+		if (num_attaches(shmid) != 3 || shmdt(child_addr) < 0)
+			_exit(1);
+		_exit(0);
+	}
+	TEST_RES(wait_exit_code(pid), _ret == 0);
+
+	// The attaches of the child are gone after it exits.
+	TEST_RES(strcmp(addr, "child"), _ret == 0);
+	TEST_RES(num_attaches(shmid), _ret == 1);
+	TEST_RES(shmctl(shmid, IPC_STAT, &ds), ds.shm_lpid == pid);
+
+	TEST_SUCC(shmdt(addr));
+	TEST_RES(num_attaches(shmid), _ret == 0);
+	TEST_ERRNO(shmdt(addr), EINVAL);
+
+	TEST_SUCC(shmctl(shmid, IPC_RMID, NULL));
+	TEST_ERRNO(shmctl(shmid, IPC_STAT, &ds), EINVAL);
+}
+END_TEST()
+
+FN_TEST(key_lookup)
+{
+	int shmid;
+
+	shmid = TEST_SUCC(
+		shmget(SHM_KEY, SHM_SIZE, IPC_CREAT | IPC_EXCL | 0600));
+	TEST_ERRNO(shmget(SHM_KEY, SHM_SIZE, IPC_CREAT | IPC_EXCL | 0600),
+		   EEXIST);
+	TEST_RES(shmget(SHM_KEY, SHM_SIZE, IPC_CREAT | 0600), _ret == shmid);
+	TEST_RES(shmget(SHM_KEY, 1, 0), _ret == shmid);
+	TEST_ERRNO(shmget(SHM_KEY, SHM_SIZE + 1, 0), EINVAL);
+
+	TEST_SUCC(shmctl(shmid, IPC_RMID, NULL));
+	TEST_ERRNO(shmget(SHM_KEY, SHM_SIZE, 0), ENOENT);
+	TEST_ERRNO(shmget(IPC_PRIVATE, 0, IPC_CREAT | 0600), EINVAL);
+}
+END_TEST()
+
+FN_TEST(remove_while_attached)
+{
+	struct shmid_ds ds;
+	char *addr;
+	int shmid;
+
+	shmid = TEST_SUCC(shmget(SHM_KEY, SHM_SIZE, IPC_CREAT | 0600));
+	addr = (char *)TEST_SUCC((long)shmat(shmid, NULL, 0));
+	TEST_SUCC(shmctl(shmid, IPC_RMID, NULL));
+
+	// The segment is destroyed only after it is detached.
+	TEST_RES(shmctl(shmid, IPC_STAT, &ds),
+		 (ds.shm_perm.mode & SHM_DEST) && ds.shm_nattch == 1);
+	TEST_ERRNO(shmget(SHM_KEY, SHM_SIZE, 0), ENOENT);
+	strcpy(addr, "still usable");
+	TEST_RES(strcmp(addr, "still usable"), _ret == 0);
+
+	TEST_SUCC(shmdt(addr));
+	TEST_ERRNO(shmctl(shmid, IPC_STAT, &ds), EINVAL);
+}
+END_TEST()
+
+FN_TEST(attach_flags)
+{
+	char *addr, *fixed_addr;
+	int shmid, zero_fd;
+
+	shmid = TEST_SUCC(shmget(IPC_PRIVATE, SHM_SIZE, IPC_CREAT | 0600));
+
+	// A read-only attach cannot be written.
+	addr = (char *)TEST_SUCC((long)shmat(shmid, NULL, SHM_RDONLY));
+	zero_fd = TEST_SUCC(open("/dev/zero", O_RDONLY));
+	TEST_ERRNO(read(zero_fd, addr, 1), EFAULT);
+	TEST_SUCC(close(zero_fd));
+	TEST_SUCC(shmdt(addr));
+
+	// Reserve a range to attach the segment at fixed addresses.
+	fixed_addr = (char *)TEST_SUCC((long)mmap(
+		NULL, SHM_SIZE * 2, PROT_NONE, MAP_PRIVATE | MAP_ANONYMOUS, -1, 0));
+	TEST_SUCC(munmap(fixed_addr, SHM_SIZE * 2));
+
+	TEST_ERRNO((long)shmat(shmid, fixed_addr + 1, 0), EINVAL);
+	TEST_RES((long)shmat(shmid, fixed_addr + 1, SHM_RND),
+		 _ret == (long)fixed_addr);
+	// The range is occupied now.
+	TEST_ERRNO((long)shmat(shmid, fixed_addr, 0), EINVAL);
+	TEST_RES((long)shmat(shmid, fixed_addr, SHM_REMAP),
+		 _ret == (long)fixed_addr);
+	TEST_ERRNO((long)shmat(shmid, NULL, SHM_REMAP), EINVAL);
+	TEST_RES(num_attaches(shmid), _ret == 1);
+
+	TEST_ERRNO(shmdt(fixed_addr + SHM_SIZE), EINVAL);
+	TEST_SUCC(shmdt(fixed_addr));
+
+	TEST_SUCC(shmctl(shmid, IPC_RMID, NULL));
+	TEST_ERRNO((long)shmat(shmid, NULL, 0), EINVAL);
+}
+END_TEST()
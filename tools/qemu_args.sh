@@ -82,6 +82,7 @@ QEMU_ARGS="\
     -device virtio-keyboard-pci,disable-legacy=on,disable-modern=off$IOMMU_DEV_EXTRA \
     -device virtio-net-pci,netdev=net01,disable-legacy=on,disable-modern=off$VIRTIO_NET_FEATURES$IOMMU_DEV_EXTRA \
     -device virtio-serial-pci,disable-legacy=on,disable-modern=off$IOMMU_DEV_EXTRA \
+    -device virtio-rng-pci,disable-legacy=on,disable-modern=off$IOMMU_DEV_EXTRA \
     -device virtconsole,chardev=mux \
     -device virtio-gpu -vga none\
     $IOMMU_EXTRA_ARGS \
@@ -97,6 +98,7 @@ MICROVM_QEMU_ARGS="\
     -device virtio-keyboard-device \
     -device virtio-net-device,netdev=net01 \
     -device virtio-serial-device \
+    -device virtio-rng-device \
     -device virtconsole,chardev=mux \
     -device virtio-gpu \
 "